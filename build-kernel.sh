@@ -17,6 +17,16 @@ echo "================================"
 # Parse command line arguments
 ARCH=${1:-all}
 BUILD_TYPE=${2:-dev}
+# Feature profile: desktop (default), server or minimal
+KERNEL_PROFILE=${KERNEL_PROFILE:-desktop}
+case "$KERNEL_PROFILE" in
+    desktop) FEATURE_FLAGS="" ;;
+    server|minimal) FEATURE_FLAGS="--no-default-features --features alloc,profile-$KERNEL_PROFILE" ;;
+    *)
+        echo -e "${RED}Error: unknown KERNEL_PROFILE '$KERNEL_PROFILE' (desktop, server, minimal)${NC}"
+        exit 1
+        ;;
+esac
 
 # Check if we're using nightly Rust (required for build-std)
 if ! rustc --version | grep -q nightly; then
@@ -32,12 +42,27 @@ if ! rustup component list | grep -q "rust-src (installed)"; then
     rustup component add rust-src
 fi
 
+# Reproducible builds: pin the embedded build timestamp to the last commit
+# (override by exporting SOURCE_DATE_EPOCH) and strip host-specific paths
+# from panic messages and debug info, so the published image hashes can be
+# regenerated independently from the same commit.
+if [ -z "${SOURCE_DATE_EPOCH:-}" ]; then
+    SOURCE_DATE_EPOCH=$(git log -1 --format=%ct 2>/dev/null || echo 0)
+fi
+export SOURCE_DATE_EPOCH
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+export RUSTFLAGS="${RUSTFLAGS:-} --remap-path-prefix=$PROJECT_ROOT=. --remap-path-prefix=${CARGO_HOME:-$HOME/.cargo}=/cargo"
+# Frame pointers let the crash handler walk the stack (kernel/src/crash).
+# The custom target specs set them too; the builtin ones need the flag.
+export RUSTFLAGS="$RUSTFLAGS -C force-frame-pointers=yes"
+echo -e "${YELLOW}SOURCE_DATE_EPOCH=$SOURCE_DATE_EPOCH${NC}"
+
 # Function to build for a specific architecture
 build_arch() {
     local arch=$1
     local target=$2
 
-    echo -e "${BLUE}Building $arch kernel...${NC}"
+    echo -e "${BLUE}Building $arch kernel ($KERNEL_PROFILE profile)...${NC}"
 
     if [ "$BUILD_TYPE" == "release" ]; then
         RELEASE_FLAG="--release"
@@ -47,9 +72,21 @@ build_arch() {
         BUILD_DIR="debug"
     fi
 
+    # The SBOM is also written next to the kernel as `veridian-release`
+    # (build-rootfs.sh installs it as /etc/veridian-release)
+    local artifact_dir="target/$(basename "$target" .json)/$BUILD_DIR"
+    mkdir -p "$artifact_dir"
+    export VERIDIAN_SBOM_OUT="$PROJECT_ROOT/$artifact_dir/veridian-release"
+
     # All architectures need -Zbuild-std for bare metal targets
-    if cargo build $RELEASE_FLAG --target "$target" -p veridian-kernel -Zbuild-std=core,compiler_builtins,alloc; then
+    if cargo build $RELEASE_FLAG $FEATURE_FLAGS --target "$target" -p veridian-kernel -Zbuild-std=core,compiler_builtins,alloc; then
         echo -e "${GREEN}$arch build successful!${NC}"
+        # Symbolized crash backtraces; patches the image, so before hashing.
+        # A host tool, so built without the kernel's target flags.
+        (cd tools/gen-symtab && RUSTFLAGS= cargo build --release --quiet)
+        tools/gen-symtab/target/release/gen-symtab "$artifact_dir/veridian-kernel"
+        (cd "$artifact_dir" && sha256sum veridian-kernel veridian-release > SHA256SUMS)
+        echo "  SHA256SUMS: $artifact_dir/SHA256SUMS"
 
         # For x86_64, create bootable disk image using bootloader 0.11+
         if [ "$arch" == "x86_64" ]; then
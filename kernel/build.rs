@@ -1,8 +1,12 @@
-use std::{env, path::PathBuf, process::Command};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 fn main() {
     let target = env::var("TARGET").expect("TARGET not set");
-    let _out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
 
     // Get git hash
     let git_hash = Command::new("git")
@@ -14,13 +18,17 @@ fn main() {
 
     println!("cargo:rustc-env=GIT_HASH={}", git_hash.trim());
 
-    // Get build timestamp
-    let build_timestamp = Command::new("date")
-        .args(["+%s"])
-        .output()
-        .ok()
-        .and_then(|output| String::from_utf8(output.stdout).ok())
-        .unwrap_or_else(|| "0".to_string());
+    // Get build timestamp. SOURCE_DATE_EPOCH (reproducible-builds.org) pins
+    // it so that rebuilding the same commit yields an identical image.
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let build_timestamp = env::var("SOURCE_DATE_EPOCH").ok().unwrap_or_else(|| {
+        Command::new("date")
+            .args(["+%s"])
+            .output()
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .unwrap_or_else(|| "0".to_string())
+    });
 
     println!("cargo:rustc-env=BUILD_TIMESTAMP={}", build_timestamp.trim());
 
@@ -28,6 +36,25 @@ fn main() {
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
     let kernel_dir = PathBuf::from(manifest_dir);
 
+    // Software bill of materials, embedded in the kernel image
+    let sbom = generate_sbom(
+        &kernel_dir,
+        &target,
+        git_hash.trim(),
+        build_timestamp.trim(),
+    );
+    let sbom_id = fnv1a64(sbom.as_bytes());
+    fs::write(Path::new(&out_dir).join("sbom.txt"), &sbom).expect("failed to write SBOM");
+    println!("cargo:rustc-env=SBOM_ID={:016x}", sbom_id);
+
+    // Optionally drop a copy next to the build artifacts (build-kernel.sh
+    // sets this so the rootfs can ship it as /etc/veridian-release)
+    println!("cargo:rerun-if-env-changed=VERIDIAN_SBOM_OUT");
+    if let Ok(path) = env::var("VERIDIAN_SBOM_OUT") {
+        let _ = fs::write(path, &sbom);
+    }
+    println!("cargo:rerun-if-changed=../Cargo.lock");
+
     // Set the linker script based on target architecture
     // Note: For x86_64 with bootloader 0.11+, we don't use a custom linker script
     // The bootloader handles loading the PIE kernel and setting up virtual mappings
@@ -52,3 +79,172 @@ fn main() {
     println!("cargo:rerun-if-changed=src/arch/aarch64/link.ld");
     println!("cargo:rerun-if-changed=src/arch/riscv64/link.ld");
 }
+
+/// Build the SBOM text: kernel identity, toolchain, enabled features, and
+/// every package linked into the kernel with its locked checksum. All lists
+/// are sorted so the output depends only on the inputs, not on environment
+/// iteration order. Fails the build if the dependency list cannot be
+/// resolved: an SBOM without it would be worthless.
+fn generate_sbom(kernel_dir: &Path, target: &str, git_hash: &str, timestamp: &str) -> String {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Cargo upper-cases feature names and turns `-` into `_`, so map each
+    // enabled one back to its spelling in the manifest
+    let declared = declared_features(kernel_dir);
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|env_name| {
+            declared
+                .iter()
+                .find(|name| name.to_uppercase().replace('-', "_") == env_name)
+                .cloned()
+                .unwrap_or_else(|| {
+                    panic!(
+                        "SBOM: CARGO_FEATURE_{} matches no [features] entry of Cargo.toml",
+                        env_name
+                    )
+                })
+        })
+        .collect();
+    features.sort();
+
+    let mut sbom = String::new();
+    sbom.push_str("# VeridianOS software bill of materials\n");
+    sbom.push_str(&format!(
+        "name: {}\nversion: {}\n",
+        env::var("CARGO_PKG_NAME").unwrap_or_default(),
+        env::var("CARGO_PKG_VERSION").unwrap_or_default()
+    ));
+    sbom.push_str(&format!("git: {}\n", git_hash));
+    sbom.push_str(&format!("target: {}\n", target));
+    sbom.push_str(&format!(
+        "profile: {}\n",
+        env::var("PROFILE").unwrap_or_default()
+    ));
+    sbom.push_str(&format!("rustc: {}\n", rustc_version.trim()));
+    sbom.push_str(&format!("build-timestamp: {}\n", timestamp));
+    sbom.push_str(&format!("features: {}\n", features.join(",")));
+
+    let lock_path = kernel_dir.join("../Cargo.lock");
+    let lock = fs::read_to_string(&lock_path)
+        .unwrap_or_else(|err| panic!("SBOM: cannot read {}: {}", lock_path.display(), err));
+    let locked = parse_lock_packages(&lock);
+
+    let packages = kernel_dependencies(kernel_dir, &features)
+        .unwrap_or_else(|err| panic!("SBOM: cannot resolve the kernel dependency graph: {}", err));
+    for (name, version) in packages {
+        let checksum = locked
+            .iter()
+            .find(|(n, v, _)| *n == name && *v == version)
+            .map_or("local", |(_, _, checksum)| checksum.as_str());
+        sbom.push_str(&format!("package: {} {} {}\n", name, version, checksum));
+    }
+    sbom
+}
+
+/// Names of the features declared in the kernel's Cargo.toml.
+fn declared_features(kernel_dir: &Path) -> Vec<String> {
+    let manifest_path = kernel_dir.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .unwrap_or_else(|err| panic!("SBOM: cannot read {}: {}", manifest_path.display(), err));
+    manifest
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != "[features]")
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, _)| name.trim().to_string())
+        .collect()
+}
+
+/// `(name, version)` of every package linked into the kernel: the normal
+/// dependencies reachable from veridian-kernel for this architecture and
+/// feature set, resolved by `cargo tree` from the locked graph. Build
+/// dependencies and the other workspace members are not part of the image
+/// and are left out.
+fn kernel_dependencies(
+    kernel_dir: &Path,
+    features: &[String],
+) -> Result<Vec<(String, String)>, String> {
+    // Dependencies are only selected by target_arch, so a built-in triple
+    // for the architecture stands in for a custom target spec
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    let triple = match arch.as_str() {
+        "x86_64" => "x86_64-unknown-none",
+        "aarch64" => "aarch64-unknown-none",
+        "riscv64" => "riscv64gc-unknown-none-elf",
+        _ => return Err(format!("unsupported architecture `{}`", arch)),
+    };
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(cargo)
+        .arg("tree")
+        .arg("--manifest-path")
+        .arg(kernel_dir.join("Cargo.toml"))
+        .args(["--locked", "--offline", "--edges", "normal"])
+        .args(["--prefix", "none", "--format", "{p}", "--target", triple])
+        .args(["--no-default-features", "--features", &features.join(",")])
+        .output()
+        .map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = stderr
+            .lines()
+            .find(|line| line.starts_with("error"))
+            .unwrap_or("cargo tree failed");
+        return Err(error.to_string());
+    }
+
+    // One `name vX.Y.Z [(source)] [(*)]` line per package, the kernel first
+    let mut packages: Vec<(String, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let version = fields.next()?.strip_prefix('v')?;
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect();
+    packages.sort();
+    packages.dedup();
+    Ok(packages)
+}
+
+/// Extract `(name, version, checksum)` for each `[[package]]` in a
+/// Cargo.lock. Path dependencies have no checksum and are marked `local`.
+fn parse_lock_packages(lock: &str) -> Vec<(String, String, String)> {
+    let mut packages = Vec::new();
+    for block in lock.split("[[package]]").skip(1) {
+        let field = |key: &str| {
+            block.lines().find_map(|line| {
+                line.strip_prefix(key)
+                    .and_then(|rest| rest.trim().strip_prefix('='))
+                    .map(|v| v.trim().trim_matches('"').to_string())
+            })
+        };
+        if let (Some(name), Some(version)) = (field("name"), field("version")) {
+            let checksum = field("checksum").unwrap_or_else(|| "local".to_string());
+            packages.push((name, version, checksum));
+        }
+    }
+    packages
+}
+
+/// FNV-1a 64-bit hash, used as a short SBOM identifier for `uname -v`.
+fn fnv1a64(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
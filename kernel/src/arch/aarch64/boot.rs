@@ -1,13 +1,18 @@
 //! AArch64 boot entry point.
 //!
 //! Includes the assembly startup code (`boot.S`) and the Rust `_start_rust`
-//! entry that prints an early banner via direct UART and calls `kernel_main`.
+//! entry that prints an early banner via direct UART, saves the device tree
+//! and calls `kernel_main`.
 
 use core::arch::global_asm;
 
 // Include the assembly boot code
 global_asm!(include_str!("boot.S"));
 
+/// Where QEMU's `virt` machine puts the device tree when it loads an ELF
+/// kernel: at the start of RAM, with no register pointing at it.
+const VIRT_RAM_BASE: usize = 0x4000_0000;
+
 /// Entry point from assembly code
 ///
 /// # Safety
@@ -16,9 +21,10 @@ global_asm!(include_str!("boot.S"));
 /// - Stack properly initialized
 /// - BSS section cleared
 /// - Running in EL1 with MMU disabled
+/// - `dtb` the device tree address from x0 at entry, or zero
 #[no_mangle]
 #[link_section = ".text.boot"]
-pub unsafe extern "C" fn _start_rust() -> ! {
+pub unsafe extern "C" fn _start_rust(dtb: usize) -> ! {
     // Use direct_uart for proper string output
     use crate::arch::aarch64::direct_uart::uart_write_str;
 
@@ -27,6 +33,12 @@ pub unsafe extern "C" fn _start_rust() -> ! {
     uart_write_str("[BOOT] Stack initialized and BSS cleared\n");
     uart_write_str("[BOOT] Preparing to enter kernel_main...\n");
 
+    // Keep the device tree (and its /chosen/bootargs command line). With
+    // the MMU off, physical addresses are directly readable.
+    if !crate::utils::fdt::init(dtb) {
+        crate::utils::fdt::init(VIRT_RAM_BASE);
+    }
+
     // Call kernel_main from main.rs
     extern "C" {
         fn kernel_main() -> !;
@@ -23,7 +23,8 @@ pub struct AArch64Context {
     /// Exception link register
     pub elr: u64,
 
-    /// Thread pointer registers
+    /// Thread pointer registers. Only TPIDR_EL0 is switched; TPIDR_EL1
+    /// holds the per-CPU area, so this slot just records the kernel stack.
     pub tpidr_el0: u64,
     pub tpidr_el1: u64,
     /// Saved TLS base for EL0
@@ -135,12 +136,12 @@ impl crate::arch::context::ThreadContext for AArch64Context {
     }
 
     fn get_kernel_stack(&self) -> usize {
-        // Kernel stack is stored in TPIDR_EL1
+        // Kernel stack is stored in the TPIDR_EL1 slot
         self.tpidr_el1 as usize
     }
 
     fn set_kernel_stack(&mut self, sp: usize) {
-        // Store kernel stack in TPIDR_EL1 for quick access
+        // Store kernel stack in the TPIDR_EL1 slot
         self.tpidr_el1 = sp as u64;
     }
 
@@ -216,10 +217,9 @@ pub unsafe extern "C" fn context_switch(current: *mut AArch64Context, next: *con
         "mrs x2, SPSR_EL1",
         "mrs x3, ELR_EL1",
         "stp x2, x3, [x0, #264]",
-        // Save thread pointers
+        // Save the EL0 thread pointer (TPIDR_EL1 is per-CPU, not per-task)
         "mrs x2, TPIDR_EL0",
-        "mrs x3, TPIDR_EL1",
-        "stp x2, x3, [x0, #280]",
+        "str x2, [x0, #280]",
         // Save translation table base
         "mrs x2, TTBR0_EL1",
         "str x2, [x0, #296]",
@@ -231,10 +231,9 @@ pub unsafe extern "C" fn context_switch(current: *mut AArch64Context, next: *con
         "msr TTBR0_EL1, x3",  // Set new page table
         "isb",                // Ensure completion
         "1:",
-        // Load thread pointers
-        "ldp x2, x3, [x1, #280]",
+        // Load the EL0 thread pointer
+        "ldr x2, [x1, #280]",
         "msr TPIDR_EL0, x2",
-        "msr TPIDR_EL1, x3",
         // Load SPSR and ELR
         "ldp x2, x3, [x1, #264]",
         "msr SPSR_EL1, x2",
@@ -416,10 +415,9 @@ pub unsafe extern "C" fn load_context(context: *const AArch64Context) {
         "ldr x1, [x0, #296]",
         "msr TTBR0_EL1, x1",
         "isb",
-        // Load thread pointers
-        "ldp x1, x2, [x0, #280]",
+        // Load the EL0 thread pointer (TPIDR_EL1 is per-CPU, not per-task)
+        "ldr x1, [x0, #280]",
         "msr TPIDR_EL0, x1",
-        "msr TPIDR_EL1, x2",
         // Load SPSR and ELR
         "ldp x1, x2, [x0, #264]",
         "msr SPSR_EL1, x1",
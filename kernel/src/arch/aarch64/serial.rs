@@ -1,10 +1,26 @@
 //! AArch64 serial output via PL011 UART.
 //!
 //! Provides a `Uart16550Compat` wrapper that writes to the PL011 UART data
-//! register at `0x0900_0000` (QEMU virt machine). Used for kernel console
-//! output on AArch64.
+//! register, at `0x0900_0000` (QEMU virt machine) unless the device tree
+//! names another one. Used for kernel console output on AArch64.
 
-use core::fmt;
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// PL011 base address used by [`create_serial_port`].
+static UART_BASE: AtomicUsize = AtomicUsize::new(0x0900_0000);
+
+/// Use the PL011 at `base` (from the device tree) for serial output.
+pub fn set_base(base: usize) {
+    UART_BASE.store(base, Ordering::Relaxed);
+}
+
+/// Base address of the console PL011.
+pub fn base() -> usize {
+    UART_BASE.load(Ordering::Relaxed)
+}
 
 pub struct Pl011Uart {
     base_addr: usize,
@@ -31,7 +47,8 @@ impl fmt::Write for Pl011Uart {
         while i < bytes.len() {
             // SAFETY: The PL011 UART data register at base_addr + 0x000 is
             // memory-mapped I/O. Writing a byte to it transmits the character.
-            // base_addr is set to 0x09000000 for QEMU's virt machine.
+            // base_addr is the PL011 from the device tree, or 0x09000000
+            // on QEMU's virt machine.
             unsafe {
                 // Direct write without FIFO check for simplicity
                 let uart_dr = (self.base_addr + UARTDR) as *mut u8;
@@ -46,7 +63,7 @@ impl fmt::Write for Pl011Uart {
 pub type SerialPort = Pl011Uart;
 
 pub fn create_serial_port() -> SerialPort {
-    Pl011Uart::new(0x0900_0000)
+    Pl011Uart::new(UART_BASE.load(Ordering::Relaxed))
 }
 
 #[doc(hidden)]
@@ -10,6 +10,8 @@
 //!   jitter.
 //! * [`try_hardware_rng`] -- attempt to read from a hardware RNG (RDRAND on
 //!   x86_64).
+//! * [`try_hardware_seed`] -- attempt to read conditioned seed material (RDSEED
+//!   on x86_64).
 //! * [`collect_timer_entropy`] -- collect 32 bytes of timer-jitter entropy.
 
 /// Read the hardware timestamp/cycle counter.
@@ -139,6 +141,102 @@ pub fn try_hardware_rng(dest: &mut [u8; 32]) -> bool {
     }
 }
 
+/// Check whether the CPU supports the RDSEED instruction.
+///
+/// Queries CPUID leaf 7 (subleaf 0) and tests EBX bit 18. EBX is copied out
+/// through a scratch register because LLVM reserves RBX.
+#[cfg(target_arch = "x86_64")]
+fn cpu_has_rdseed() -> bool {
+    // SAFETY: CPUID leaf 0 and leaf 7 are read-only, side-effect-free queries
+    // that are always available in long mode. RBX is saved and restored around
+    // each CPUID for the same reason as in `cpu_has_rdrand`.
+    let max_leaf: u32;
+    unsafe {
+        core::arch::asm!(
+            "push rbx",
+            "xor eax, eax",
+            "cpuid",
+            "pop rbx",
+            out("eax") max_leaf,
+            out("ecx") _,
+            out("edx") _,
+            options(nomem, preserves_flags),
+        );
+    }
+    if max_leaf < 7 {
+        return false;
+    }
+
+    let ebx: u32;
+    unsafe {
+        core::arch::asm!(
+            "push rbx",
+            "mov eax, 7",
+            "xor ecx, ecx",
+            "cpuid",
+            "mov {0:e}, ebx",
+            "pop rbx",
+            out(reg) ebx,
+            out("eax") _,
+            out("ecx") _,
+            out("edx") _,
+            options(nomem, preserves_flags),
+        );
+    }
+    (ebx & (1 << 18)) != 0
+}
+
+/// Attempt to read 32 bytes of seed-grade entropy from the hardware.
+///
+/// Unlike [`try_hardware_rng`], whose output comes from a DRBG that the CPU
+/// reseeds internally, this reads the conditioned entropy source directly
+/// and is the preferred input for (re)seeding a software CSPRNG. RDSEED
+/// fails transiently when the source is drained, so each word is retried a
+/// bounded number of times.
+///
+/// * **x86_64**: `RDSEED`, when CPUID reports support.
+/// * **AArch64/RISC-V**: always returns `false`.
+pub fn try_hardware_seed(dest: &mut [u8; 32]) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if !cpu_has_rdseed() {
+            return false;
+        }
+
+        use core::arch::x86_64::_rdseed64_step;
+
+        // SAFETY: _rdseed64_step executes RDSEED, which writes a random u64
+        // into `value` and returns 0 if no entropy was available. Support was
+        // verified via CPUID above, so the instruction will not fault.
+        unsafe {
+            for chunk in dest.chunks_exact_mut(8) {
+                let mut value: u64 = 0;
+                let mut attempts = 0;
+                let mut success = false;
+                while attempts < 100 {
+                    if _rdseed64_step(&mut value) != 0 {
+                        success = true;
+                        break;
+                    }
+                    core::hint::spin_loop();
+                    attempts += 1;
+                }
+                if !success {
+                    return false;
+                }
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        true
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let _ = dest;
+        false
+    }
+}
+
 /// Collect 32 bytes of timer-jitter entropy.
 ///
 /// Uses [`read_timestamp`] to sample the hardware counter with variable-work
@@ -14,7 +14,8 @@ pub struct RiscVContext {
     pub sp: usize,
     /// Global pointer
     pub gp: usize,
-    /// Thread pointer
+    /// Thread pointer. Not switched in the kernel, where tp holds the
+    /// per-CPU area.
     pub tp: usize,
     /// Temporary registers
     pub t0: usize,
@@ -218,12 +219,12 @@ impl crate::arch::context::ThreadContext for RiscVContext {
     }
 
     fn get_kernel_stack(&self) -> usize {
-        // Kernel stack pointer is stored in thread pointer (tp)
+        // Kernel stack pointer is stored in the tp slot
         self.tp
     }
 
     fn set_kernel_stack(&mut self, sp: usize) {
-        // Store kernel stack in thread pointer for quick access
+        // Store kernel stack in the tp slot
         self.tp = sp;
     }
 
@@ -281,8 +282,7 @@ pub unsafe extern "C" fn context_switch(current: *mut RiscVContext, next: *const
         "sd sp, 8(a0)",
         // Save global pointer
         "sd gp, 16(a0)",
-        // Save thread pointer
-        "sd tp, 24(a0)",
+        // tp holds the per-CPU area and is not switched
         // Save temporary registers
         "sd t0, 32(a0)",
         "sd t1, 40(a0)",
@@ -341,7 +341,6 @@ pub unsafe extern "C" fn context_switch(current: *mut RiscVContext, next: *const
         "ld ra, 0(a1)",
         "ld sp, 8(a1)",
         "ld gp, 16(a1)",
-        "ld tp, 24(a1)",
         // Load temporary registers
         "ld t0, 32(a1)",
         "ld t1, 40(a1)",
@@ -576,7 +575,6 @@ pub unsafe extern "C" fn load_context(context: *const RiscVContext) {
         "ld ra, 0(a0)",
         "ld sp, 8(a0)",
         "ld gp, 16(a0)",
-        "ld tp, 24(a0)",
         // Load temporary registers
         "ld t0, 32(a0)",
         "ld t1, 40(a0)",
@@ -4,7 +4,7 @@
 
 // SBI extension IDs and function IDs are defined per the RISC-V SBI
 // specification for completeness. They will be needed as SBI call coverage
-// expands (e.g., HSM for multi-hart boot, SRST for system reset).
+// expands (e.g., HSM for multi-hart boot).
 
 /// SBI extension IDs
 const SBI_EXT_BASE: usize = 0x10;
@@ -13,12 +13,24 @@ const SBI_EXT_IPI: usize = 0x735049; // "sPI"
 const SBI_EXT_RFENCE: usize = 0x52464E43; // "RFNC"
 #[allow(dead_code)] // SBI extension ID per RISC-V SBI spec
 const SBI_EXT_HSM: usize = 0x48534D; // "HSM"
-#[allow(dead_code)] // SBI extension ID per RISC-V SBI spec
 const SBI_EXT_SRST: usize = 0x53525354; // "SRST"
 
 /// SBI function IDs for timer extension
 const SBI_TIMER_SET_TIMER: usize = 0;
 
+/// SBI function ID for the system reset extension
+const SBI_SRST_SYSTEM_RESET: usize = 0;
+
+/// Legacy (v0.1) shutdown extension ID
+const SBI_LEGACY_SHUTDOWN: usize = 0x08;
+
+/// SRST reset types
+pub const SBI_RESET_TYPE_SHUTDOWN: usize = 0;
+pub const SBI_RESET_TYPE_COLD_REBOOT: usize = 1;
+
+/// SRST reset reasons
+pub const SBI_RESET_REASON_NONE: usize = 0;
+
 /// SBI return value
 #[derive(Debug, Clone, Copy)]
 pub struct SbiRet {
@@ -90,6 +102,18 @@ pub fn probe_extension(extension_id: usize) -> bool {
     ret.value != 0
 }
 
+/// Reset or shut down the system (SRST extension)
+///
+/// Does not return on success.
+pub fn system_reset(reset_type: usize, reason: usize) -> SbiRet {
+    sbi_call(SBI_EXT_SRST, SBI_SRST_SYSTEM_RESET, reset_type, reason, 0)
+}
+
+/// Legacy shutdown, for firmware without the SRST extension
+pub fn legacy_shutdown() {
+    sbi_call(SBI_LEGACY_SHUTDOWN, 0, 0, 0, 0);
+}
+
 /// Legacy console putchar (for early boot)
 pub fn console_putchar(ch: u8) {
     sbi_call(0x01, 0, ch as usize, 0, 0);
@@ -127,6 +151,9 @@ pub fn init() {
         rfence_available
     );
 
+    let srst_available = probe_extension(SBI_EXT_SRST);
+    println!("[SBI] System reset extension available: {}", srst_available);
+
     if !timer_available {
         println!("[SBI] WARNING: Timer extension not available!");
     }
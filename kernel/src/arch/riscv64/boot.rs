@@ -1,16 +1,18 @@
 //! RISC-V 64 boot entry point.
 //!
 //! Includes the assembly startup code (`boot.S`) and the Rust `_start_rust`
-//! entry that prints an early banner via SBI console putchar and calls
-//! `kernel_main`.
+//! entry that prints an early banner via SBI console putchar, saves the device
+//! tree and calls `kernel_main`.
 
 use core::arch::global_asm;
 
 // Include the assembly boot code
 global_asm!(include_str!("boot.S"));
 
+/// Entry point from assembly code; `dtb` is the device tree address passed
+/// by the SBI firmware.
 #[no_mangle]
-pub extern "C" fn _start_rust() -> ! {
+pub extern "C" fn _start_rust(dtb: usize) -> ! {
     // SAFETY: sbi_putchar invokes the SBI legacy console putchar (ecall with
     // a7=0x01). Used for early boot output before any Rust infrastructure is
     // available. Always safe to call from supervisor mode.
@@ -23,6 +25,12 @@ pub extern "C" fn _start_rust() -> ! {
         sbi_putchar(b'\n');
     }
 
+    // SAFETY: OpenSBI passes the physical address of a valid device tree
+    // (or zero); paging is still off, so it is directly readable.
+    unsafe {
+        crate::utils::fdt::init(dtb);
+    }
+
     // Call the kernel main function from main.rs
     extern "C" {
         fn kernel_main() -> !;
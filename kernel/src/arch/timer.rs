@@ -249,6 +249,17 @@ pub fn get_timestamp_ms() -> u64 {
     }
 }
 
+/// Get the current timestamp in nanoseconds since boot.
+pub fn get_timestamp_ns() -> u64 {
+    let tps = hw_ticks_per_second();
+    if tps == 0 {
+        return 0;
+    }
+    // Split off whole seconds so the scaling cannot overflow
+    let ticks = read_hw_timestamp();
+    (ticks / tps) * 1_000_000_000 + (ticks % tps) * 1_000_000_000 / tps
+}
+
 /// Program the next timer interrupt.
 pub fn set_hw_timer(ticks: u64) {
     #[cfg(target_arch = "x86_64")]
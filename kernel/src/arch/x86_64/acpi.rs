@@ -4,6 +4,8 @@
 //! - MADT (Multiple APIC Description Table): CPU enumeration, I/O APIC, ISA
 //!   overrides
 //! - MCFG (PCI Express config space base addresses)
+//! - FADT (Fixed ACPI Description Table): PM1/GPE register blocks, PM timer,
+//!   SCI vector, reset register, legacy device flags
 //!
 //! Not in scope: Full AML interpreter, ACPI namespace, runtime ACPI methods.
 
@@ -25,6 +27,7 @@ const MCFG_SIGNATURE: &[u8; 4] = b"MCFG";
 const DMAR_SIGNATURE: &[u8; 4] = b"DMAR";
 const SRAT_SIGNATURE: &[u8; 4] = b"SRAT";
 const SLIT_SIGNATURE: &[u8; 4] = b"SLIT";
+const FADT_SIGNATURE: &[u8; 4] = b"FACP";
 
 // ---------------------------------------------------------------------------
 // MADT entry types
@@ -118,6 +121,102 @@ pub struct McfgEntry {
     pub end_bus: u8,
 }
 
+/// ACPI Generic Address Structure (register location in some address space).
+#[derive(Debug, Clone, Copy)]
+pub struct GenericAddress {
+    /// Address space ID (0 = system memory, 1 = system I/O, 2 = PCI config).
+    pub address_space: u8,
+    /// Register width in bits.
+    pub bit_width: u8,
+    /// Bit offset of the register within the address.
+    pub bit_offset: u8,
+    /// Access size (0 = undefined, 1 = byte, 2 = word, 3 = dword, 4 = qword).
+    pub access_size: u8,
+    /// Register address in the given address space.
+    pub address: u64,
+}
+
+/// Power-management and platform information from the FADT.
+#[derive(Debug, Clone, Copy)]
+pub struct FadtInfo {
+    /// Physical address of the DSDT (X_DSDT if present, else DSDT).
+    pub dsdt_address: u64,
+    /// ISA IRQ the SCI is wired to.
+    pub sci_int: u16,
+    /// SMI command port (0 if the system is always in ACPI mode).
+    pub smi_cmd: u32,
+    /// Value to write to SMI_CMD to enable ACPI mode.
+    pub acpi_enable: u8,
+    /// Value to write to SMI_CMD to disable ACPI mode.
+    pub acpi_disable: u8,
+    /// PM1a event block I/O port.
+    pub pm1a_evt_blk: u32,
+    /// PM1b event block I/O port (0 if not present).
+    pub pm1b_evt_blk: u32,
+    /// PM1a control block I/O port.
+    pub pm1a_cnt_blk: u32,
+    /// PM1b control block I/O port (0 if not present).
+    pub pm1b_cnt_blk: u32,
+    /// PM timer block I/O port (0 if not present).
+    pub pm_tmr_blk: u32,
+    /// GPE0 block I/O port.
+    pub gpe0_blk: u32,
+    /// GPE1 block I/O port (0 if not present).
+    pub gpe1_blk: u32,
+    /// PM1 event block length in bytes.
+    pub pm1_evt_len: u8,
+    /// PM1 control block length in bytes.
+    pub pm1_cnt_len: u8,
+    /// PM timer block length in bytes.
+    pub pm_tmr_len: u8,
+    /// GPE0 block length in bytes.
+    pub gpe0_blk_len: u8,
+    /// GPE1 block length in bytes.
+    pub gpe1_blk_len: u8,
+    /// CMOS RTC index of the century register (0 if not supported).
+    pub century: u8,
+    /// IA-PC boot architecture flags.
+    pub iapc_boot_arch: u16,
+    /// Fixed feature flags.
+    pub flags: u32,
+    /// Reset register, if the platform supports reset through it.
+    pub reset_reg: Option<GenericAddress>,
+    /// Value to write to the reset register.
+    pub reset_value: u8,
+}
+
+impl FadtInfo {
+    /// IAPC_BOOT_ARCH bit 1: an 8042 keyboard controller is present.
+    pub const BOOT_ARCH_8042: u16 = 1 << 1;
+    /// FLAGS bit 8: the PM timer counts 32 bits (24 otherwise).
+    pub const FLAG_TMR_VAL_EXT: u32 = 1 << 8;
+    /// FLAGS bit 10: the reset register is supported.
+    pub const FLAG_RESET_REG_SUP: u32 = 1 << 10;
+    /// FLAGS bit 20: hardware-reduced ACPI (no fixed PM hardware).
+    pub const FLAG_HW_REDUCED_ACPI: u32 = 1 << 20;
+
+    /// Whether an 8042 keyboard controller is present.
+    ///
+    /// ACPI 1.0 tables leave IAPC_BOOT_ARCH zero, which is read as present.
+    pub fn has_8042(&self) -> bool {
+        self.iapc_boot_arch == 0 || self.iapc_boot_arch & Self::BOOT_ARCH_8042 != 0
+    }
+
+    /// Width of the PM timer counter in bits.
+    pub fn pm_timer_bits(&self) -> u32 {
+        if self.flags & Self::FLAG_TMR_VAL_EXT != 0 {
+            32
+        } else {
+            24
+        }
+    }
+
+    /// Whether the platform is hardware-reduced ACPI.
+    pub fn is_hw_reduced(&self) -> bool {
+        self.flags & Self::FLAG_HW_REDUCED_ACPI != 0
+    }
+}
+
 /// Parsed ACPI information, populated by `init()`.
 #[derive(Debug)]
 pub struct AcpiInfo {
@@ -167,6 +266,8 @@ pub struct AcpiInfo {
     pub slit_address: u64,
     /// Length of the SLIT table in bytes.
     pub slit_length: u32,
+    /// FADT contents, if the table was found.
+    pub fadt: Option<FadtInfo>,
 }
 
 impl AcpiInfo {
@@ -195,6 +296,7 @@ impl AcpiInfo {
             has_slit: false,
             slit_address: 0,
             slit_length: 0,
+            fadt: None,
         }
     }
 
@@ -203,6 +305,31 @@ impl AcpiInfo {
         self.io_apics[0].map_or(0xFEC0_0000, |a| a.address)
     }
 
+    /// Find the I/O APIC whose input range starts at or below `gsi`.
+    ///
+    /// Returns the I/O APIC with the highest GSI base not exceeding `gsi`;
+    /// the caller checks the pin against the controller's entry count.
+    pub fn io_apic_for_gsi(&self, gsi: u32) -> Option<MadtIoApic> {
+        self.io_apics[..self.io_apic_count]
+            .iter()
+            .flatten()
+            .filter(|a| a.gsi_base <= gsi)
+            .max_by_key(|a| a.gsi_base)
+            .copied()
+    }
+
+    /// ECAM base address covering `bus` in PCI segment `segment`.
+    ///
+    /// The returned base is for bus 0 of the segment, so it can be passed
+    /// straight to the ECAM accessors in `drivers::pci`.
+    pub fn ecam_base(&self, segment: u16, bus: u8) -> Option<u64> {
+        self.mcfg_entries[..self.mcfg_count]
+            .iter()
+            .flatten()
+            .find(|m| m.segment_group == segment && (m.start_bus..=m.end_bus).contains(&bus))
+            .map(|m| m.base_address)
+    }
+
     /// Look up the GSI for a given ISA IRQ, applying interrupt source
     /// overrides.
     pub fn irq_to_gsi(&self, irq: u8) -> (u32, bool, bool) {
@@ -350,6 +477,71 @@ struct McfgAllocation {
     _reserved: u32,
 }
 
+/// ACPI Generic Address Structure as laid out in tables (12 bytes).
+#[repr(C, packed)]
+struct RawGenericAddress {
+    address_space: u8,
+    bit_width: u8,
+    bit_offset: u8,
+    access_size: u8,
+    address: u64,
+}
+
+/// FADT fields present since ACPI 1.0 (116 bytes including the header).
+#[repr(C, packed)]
+struct FadtTable {
+    sdt: AcpiSdtHeader,
+    firmware_ctrl: u32,
+    dsdt: u32,
+    _reserved0: u8,
+    preferred_pm_profile: u8,
+    sci_int: u16,
+    smi_cmd: u32,
+    acpi_enable: u8,
+    acpi_disable: u8,
+    s4bios_req: u8,
+    pstate_cnt: u8,
+    pm1a_evt_blk: u32,
+    pm1b_evt_blk: u32,
+    pm1a_cnt_blk: u32,
+    pm1b_cnt_blk: u32,
+    pm2_cnt_blk: u32,
+    pm_tmr_blk: u32,
+    gpe0_blk: u32,
+    gpe1_blk: u32,
+    pm1_evt_len: u8,
+    pm1_cnt_len: u8,
+    pm2_cnt_len: u8,
+    pm_tmr_len: u8,
+    gpe0_blk_len: u8,
+    gpe1_blk_len: u8,
+    gpe1_base: u8,
+    cst_cnt: u8,
+    p_lvl2_lat: u16,
+    p_lvl3_lat: u16,
+    flush_size: u16,
+    flush_stride: u16,
+    duty_offset: u8,
+    duty_width: u8,
+    day_alrm: u8,
+    mon_alrm: u8,
+    century: u8,
+    iapc_boot_arch: u16,
+    _reserved1: u8,
+    flags: u32,
+}
+
+/// FADT fields added in ACPI 2.0, immediately following [`FadtTable`].
+#[repr(C, packed)]
+struct FadtExtension {
+    reset_reg: RawGenericAddress,
+    reset_value: u8,
+    arm_boot_arch: u16,
+    fadt_minor_version: u8,
+    x_firmware_ctrl: u64,
+    x_dsdt: u64,
+}
+
 // ---------------------------------------------------------------------------
 // Checksum validation
 // ---------------------------------------------------------------------------
@@ -547,6 +739,86 @@ fn parse_mcfg(header_vaddr: usize, info: &mut AcpiInfo) {
     }
 }
 
+/// Parse the FADT (Fixed ACPI Description Table).
+fn parse_fadt(header_vaddr: usize, info: &mut AcpiInfo) {
+    // SAFETY: header_vaddr points to a valid ACPI SDT header.
+    let sdt = unsafe { &*(header_vaddr as *const AcpiSdtHeader) };
+    let table_len = { sdt.length } as usize;
+
+    if table_len < core::mem::size_of::<FadtTable>() {
+        println!("[ACPI] FADT too short (len={}), ignored", table_len);
+        return;
+    }
+
+    // SAFETY: the table is at least as long as the ACPI 1.0 FADT layout.
+    let fadt = unsafe { &*(header_vaddr as *const FadtTable) };
+    let mut parsed = FadtInfo {
+        dsdt_address: { fadt.dsdt } as u64,
+        sci_int: { fadt.sci_int },
+        smi_cmd: { fadt.smi_cmd },
+        acpi_enable: fadt.acpi_enable,
+        acpi_disable: fadt.acpi_disable,
+        pm1a_evt_blk: { fadt.pm1a_evt_blk },
+        pm1b_evt_blk: { fadt.pm1b_evt_blk },
+        pm1a_cnt_blk: { fadt.pm1a_cnt_blk },
+        pm1b_cnt_blk: { fadt.pm1b_cnt_blk },
+        pm_tmr_blk: { fadt.pm_tmr_blk },
+        gpe0_blk: { fadt.gpe0_blk },
+        gpe1_blk: { fadt.gpe1_blk },
+        pm1_evt_len: fadt.pm1_evt_len,
+        pm1_cnt_len: fadt.pm1_cnt_len,
+        pm_tmr_len: fadt.pm_tmr_len,
+        gpe0_blk_len: fadt.gpe0_blk_len,
+        gpe1_blk_len: fadt.gpe1_blk_len,
+        century: fadt.century,
+        // IAPC_BOOT_ARCH is reserved in ACPI 1.0 FADTs.
+        iapc_boot_arch: if { sdt.revision } >= 2 {
+            {
+                fadt.iapc_boot_arch
+            }
+        } else {
+            0
+        },
+        flags: { fadt.flags },
+        reset_reg: None,
+        reset_value: 0,
+    };
+
+    let ext_end = core::mem::size_of::<FadtTable>() + core::mem::size_of::<FadtExtension>();
+    if table_len >= ext_end {
+        // SAFETY: the table extends past the ACPI 2.0 fields read here.
+        let ext = unsafe {
+            &*((header_vaddr + core::mem::size_of::<FadtTable>()) as *const FadtExtension)
+        };
+        let x_dsdt = { ext.x_dsdt };
+        if x_dsdt != 0 {
+            parsed.dsdt_address = x_dsdt;
+        }
+        if parsed.flags & FadtInfo::FLAG_RESET_REG_SUP != 0 {
+            parsed.reset_reg = Some(GenericAddress {
+                address_space: ext.reset_reg.address_space,
+                bit_width: ext.reset_reg.bit_width,
+                bit_offset: ext.reset_reg.bit_offset,
+                access_size: ext.reset_reg.access_size,
+                address: { ext.reset_reg.address },
+            });
+            parsed.reset_value = ext.reset_value;
+        }
+    }
+
+    println!(
+        "[ACPI] FADT: SCI={}, PM1a_EVT={:#x}, PM1a_CNT={:#x}, PM_TMR={:#x} ({}-bit), 8042={}",
+        parsed.sci_int,
+        parsed.pm1a_evt_blk,
+        parsed.pm1a_cnt_blk,
+        parsed.pm_tmr_blk,
+        parsed.pm_timer_bits(),
+        parsed.has_8042()
+    );
+
+    info.fadt = Some(parsed);
+}
+
 /// Parse a single ACPI table identified by its header.
 fn parse_table(header_vaddr: usize, info: &mut AcpiInfo) {
     // SAFETY: header_vaddr points to a valid ACPI SDT header.
@@ -567,6 +839,8 @@ fn parse_table(header_vaddr: usize, info: &mut AcpiInfo) {
         parse_madt(header_vaddr, info);
     } else if &sig == MCFG_SIGNATURE {
         parse_mcfg(header_vaddr, info);
+    } else if &sig == FADT_SIGNATURE {
+        parse_fadt(header_vaddr, info);
     } else if &sig == DMAR_SIGNATURE {
         // Record DMAR presence and location for the IOMMU driver to parse.
         // The DMAR table has a complex structure (DRHD, RMRR, ATSR entries)
@@ -802,11 +1076,12 @@ pub fn init() -> KernelResult<()> {
     }
 
     println!(
-        "[ACPI] Initialization complete: {} CPUs, {} I/O APICs, MADT={}, MCFG={}",
+        "[ACPI] Initialization complete: {} CPUs, {} I/O APICs, MADT={}, MCFG={}, FADT={}",
         info.cpu_count(),
         info.io_apic_count,
         info.has_madt,
-        info.has_mcfg
+        info.has_mcfg,
+        info.fadt.is_some()
     );
 
     *ACPI_INFO.lock() = Some(info);
@@ -904,6 +1179,41 @@ pub fn dump() {
         println!("\n--- PCIe ECAM: not available ---");
     }
 
+    if let Some(ref fadt) = info.fadt {
+        println!("\n--- FADT ---");
+        println!(
+            "  SCI IRQ: {}, SMI_CMD: {:#x} (enable={:#x}, disable={:#x})",
+            fadt.sci_int, fadt.smi_cmd, fadt.acpi_enable, fadt.acpi_disable
+        );
+        println!(
+            "  PM1a EVT/CNT: {:#x}/{:#x}, PM1b EVT/CNT: {:#x}/{:#x}",
+            fadt.pm1a_evt_blk, fadt.pm1a_cnt_blk, fadt.pm1b_evt_blk, fadt.pm1b_cnt_blk
+        );
+        println!(
+            "  PM timer: {:#x} ({}-bit), GPE0: {:#x} (len={})",
+            fadt.pm_tmr_blk,
+            fadt.pm_timer_bits(),
+            fadt.gpe0_blk,
+            fadt.gpe0_blk_len
+        );
+        match fadt.reset_reg {
+            Some(reg) => println!(
+                "  Reset register: space={}, addr={:#x}, value={:#x}",
+                reg.address_space, reg.address, fadt.reset_value
+            ),
+            None => println!("  Reset register: not supported"),
+        }
+        println!(
+            "  Flags: {:#x}, boot arch: {:#x} (8042: {}), century: {}",
+            fadt.flags,
+            fadt.iapc_boot_arch,
+            fadt.has_8042(),
+            fadt.century
+        );
+    } else {
+        println!("\n--- FADT: not available ---");
+    }
+
     println!(
         "\nSummary: {} usable CPUs, {} I/O APICs, {} ISOs, {} MCFG entries",
         info.cpu_count(),
@@ -913,6 +1223,11 @@ pub fn dump() {
     );
 }
 
+/// Parsed FADT, if ACPI is initialized and the firmware provided one.
+pub fn fadt() -> Option<FadtInfo> {
+    with_acpi_info(|info| info.fadt).flatten()
+}
+
 /// Find SRAT table data. Returns a slice of the raw SRAT table if present.
 pub fn find_srat() -> Option<&'static [u8]> {
     with_acpi_info(|info| {
@@ -287,73 +287,6 @@ static LAST_WAKE_EVENT: AtomicU8 = AtomicU8::new(5); // Unknown
 // Supported sleep states bitmask (bit N = SN supported)
 static SUPPORTED_STATES: AtomicU16 = AtomicU16::new(0);
 
-// ---------------------------------------------------------------------------
-// FADT table signature and structure
-// ---------------------------------------------------------------------------
-
-const FADT_SIGNATURE: &[u8; 4] = b"FACP";
-
-/// FADT (Fixed ACPI Description Table) -- partial, only PM-relevant fields.
-/// Full FADT is 276 bytes for ACPI 6.0; we only read what we need.
-#[repr(C, packed)]
-struct FadtHeader {
-    /// Standard ACPI SDT header (36 bytes).
-    signature: [u8; 4],
-    length: u32,
-    revision: u8,
-    checksum: u8,
-    oem_id: [u8; 6],
-    oem_table_id: [u8; 8],
-    oem_revision: u32,
-    creator_id: u32,
-    creator_revision: u32,
-    /// Offset 36: FIRMWARE_CTRL.
-    firmware_ctrl: u32,
-    /// Offset 40: DSDT address.
-    dsdt: u32,
-    /// Offset 44: Reserved (ACPI 1.0 INT_MODEL).
-    _reserved1: u8,
-    /// Offset 45: Preferred PM profile.
-    preferred_pm_profile: u8,
-    /// Offset 46: SCI interrupt vector.
-    sci_int: u16,
-    /// Offset 48: SMI command port.
-    smi_cmd: u32,
-    /// Offset 52: ACPI enable value.
-    acpi_enable: u8,
-    /// Offset 53: ACPI disable value.
-    acpi_disable: u8,
-    /// Offset 54-55: S4BIOS_REQ, PSTATE_CNT.
-    _s4bios_req: u8,
-    _pstate_cnt: u8,
-    /// Offset 56: PM1a event block.
-    pm1a_evt_blk: u32,
-    /// Offset 60: PM1b event block.
-    pm1b_evt_blk: u32,
-    /// Offset 64: PM1a control block.
-    pm1a_cnt_blk: u32,
-    /// Offset 68: PM1b control block.
-    pm1b_cnt_blk: u32,
-    /// Offset 72: PM2 control block.
-    _pm2_cnt_blk: u32,
-    /// Offset 76: PM timer block.
-    _pm_tmr_blk: u32,
-    /// Offset 80: GPE0 block.
-    gpe0_blk: u32,
-    /// Offset 84: GPE1 block.
-    _gpe1_blk: u32,
-    /// Offset 88: PM1 event length.
-    pm1_evt_len: u8,
-    /// Offset 89: PM1 control length.
-    pm1_cnt_len: u8,
-    /// Offset 90: PM2 control length.
-    _pm2_cnt_len: u8,
-    /// Offset 91: PM timer length.
-    _pm_tmr_len: u8,
-    /// Offset 92: GPE0 block length.
-    gpe0_blk_len: u8,
-}
-
 // ---------------------------------------------------------------------------
 // Initialization
 // ---------------------------------------------------------------------------
@@ -374,12 +307,10 @@ pub fn acpi_pm_init() -> KernelResult<()> {
 
     println!("[ACPI-PM] Initializing power management...");
 
-    // Find FADT in ACPI tables by scanning the RSDT/XSDT.
-    // For now, use default QEMU values which work for the common case.
     let mut info = FadtPmInfo::new();
 
-    // Try to parse FADT from firmware tables.
-    if let Some(fadt_info) = parse_fadt_from_tables() {
+    // Use the FADT parsed by acpi::init(), falling back to QEMU defaults.
+    if let Some(fadt_info) = fadt_pm_info() {
         info = fadt_info;
         println!(
             "[ACPI-PM] FADT parsed: PM1a_CNT={:#x}, PM1a_EVT={:#x}, SCI={}",
@@ -464,127 +395,9 @@ pub fn acpi_pm_init() -> KernelResult<()> {
     Ok(())
 }
 
-/// Attempt to parse FADT from ACPI table hierarchy.
-fn parse_fadt_from_tables() -> Option<FadtPmInfo> {
-    // Access boot info to get RSDP, then walk RSDT/XSDT looking for FACP.
-    #[allow(static_mut_refs)]
-    let rsdp_phys = unsafe {
-        super::boot::BOOT_INFO
-            .as_ref()
-            .and_then(|bi| bi.rsdp_addr.into_option())
-    }?;
-
-    let rsdp_vaddr = super::msr::phys_to_virt(rsdp_phys as usize)?;
-
-    // SAFETY: rsdp_vaddr points to a valid RSDP mapped by the bootloader.
-    let rsdp = unsafe { &*(rsdp_vaddr as *const FadtRsdp) };
-    if &rsdp.signature != b"RSD PTR " {
-        return None;
-    }
-
-    // Use XSDT for ACPI 2.0+, RSDT otherwise.
-    if rsdp.revision >= 2 {
-        // SAFETY: ACPI 2.0 RSDP has xsdt_address at offset 24.
-        let rsdp2 = unsafe { &*(rsdp_vaddr as *const FadtRsdp2) };
-        let xsdt_phys = { rsdp2.xsdt_address } as usize;
-        if xsdt_phys != 0 {
-            let xsdt_vaddr = super::msr::phys_to_virt(xsdt_phys)?;
-            return find_fadt_in_xsdt(xsdt_vaddr);
-        }
-    }
-
-    let rsdt_phys = { rsdp.rsdt_address } as usize;
-    let rsdt_vaddr = super::msr::phys_to_virt(rsdt_phys)?;
-    find_fadt_in_rsdt(rsdt_vaddr)
-}
-
-// Minimal RSDP structs for FADT lookup (duplicated from acpi.rs to avoid
-// coupling to private types).
-#[repr(C, packed)]
-struct FadtRsdp {
-    signature: [u8; 8],
-    checksum: u8,
-    oem_id: [u8; 6],
-    revision: u8,
-    rsdt_address: u32,
-}
-
-#[repr(C, packed)]
-struct FadtRsdp2 {
-    base: FadtRsdp,
-    length: u32,
-    xsdt_address: u64,
-    extended_checksum: u8,
-    _reserved: [u8; 3],
-}
-
-#[repr(C, packed)]
-struct SdtHeader {
-    signature: [u8; 4],
-    length: u32,
-    revision: u8,
-    checksum: u8,
-    oem_id: [u8; 6],
-    oem_table_id: [u8; 8],
-    oem_revision: u32,
-    creator_id: u32,
-    creator_revision: u32,
-}
-
-fn find_fadt_in_rsdt(rsdt_vaddr: usize) -> Option<FadtPmInfo> {
-    // SAFETY: rsdt_vaddr points to a valid RSDT.
-    let sdt = unsafe { &*(rsdt_vaddr as *const SdtHeader) };
-    let len = { sdt.length } as usize;
-    let header_size = core::mem::size_of::<SdtHeader>();
-    let num_entries = (len.saturating_sub(header_size)) / 4;
-
-    for i in 0..num_entries {
-        let ptr_addr = rsdt_vaddr + header_size + i * 4;
-        // SAFETY: ptr_addr is within RSDT bounds.
-        let phys_addr = unsafe { *(ptr_addr as *const u32) } as usize;
-        if let Some(vaddr) = super::msr::phys_to_virt(phys_addr) {
-            if let Some(info) = try_parse_fadt(vaddr) {
-                return Some(info);
-            }
-        }
-    }
-    None
-}
-
-fn find_fadt_in_xsdt(xsdt_vaddr: usize) -> Option<FadtPmInfo> {
-    // SAFETY: xsdt_vaddr points to a valid XSDT.
-    let sdt = unsafe { &*(xsdt_vaddr as *const SdtHeader) };
-    let len = { sdt.length } as usize;
-    let header_size = core::mem::size_of::<SdtHeader>();
-    let num_entries = (len.saturating_sub(header_size)) / 8;
-
-    for i in 0..num_entries {
-        let ptr_addr = xsdt_vaddr + header_size + i * 8;
-        // SAFETY: ptr_addr is within XSDT bounds.
-        let phys_addr = unsafe { *(ptr_addr as *const u64) } as usize;
-        if let Some(vaddr) = super::msr::phys_to_virt(phys_addr) {
-            if let Some(info) = try_parse_fadt(vaddr) {
-                return Some(info);
-            }
-        }
-    }
-    None
-}
-
-fn try_parse_fadt(vaddr: usize) -> Option<FadtPmInfo> {
-    // SAFETY: vaddr points to a valid ACPI table header.
-    let sdt = unsafe { &*(vaddr as *const SdtHeader) };
-    if &{ sdt.signature } != FADT_SIGNATURE {
-        return None;
-    }
-
-    let len = { sdt.length } as usize;
-    if len < core::mem::size_of::<FadtHeader>() {
-        return None;
-    }
-
-    // SAFETY: vaddr points to a valid FADT with sufficient length.
-    let fadt = unsafe { &*(vaddr as *const FadtHeader) };
+/// PM register layout from the FADT parsed by `acpi::init()`.
+fn fadt_pm_info() -> Option<FadtPmInfo> {
+    let fadt = super::acpi::fadt()?;
 
     let mut info = FadtPmInfo::new();
     info.pm1a_cnt_blk = fadt.pm1a_cnt_blk as u16;
@@ -1083,6 +896,10 @@ pub fn acpi_handle_sci() {
         write_pm1a_sts(&info, PWRBTN_STS);
         LAST_WAKE_EVENT.store(AcpiWakeEvent::PowerButton as u8, Ordering::Release);
         println!("[ACPI-PM] Power button press detected");
+        crate::power::system::request(
+            crate::power::system::PowerAction::PowerOff,
+            crate::power::system::RequestReason::PowerButton,
+        );
     }
 
     // PM timer overflow.
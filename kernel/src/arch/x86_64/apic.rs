@@ -5,9 +5,11 @@
 //! additive to the existing PIC (8259) setup -- the PIC remains as a fallback
 //! while the APIC handles advanced interrupt routing.
 //!
-//! The Local APIC is memory-mapped at 0xFEE0_0000 (identity-mapped by the
-//! bootloader). The I/O APIC is at 0xFEC0_0000 with indirect register access
-//! via IOREGSEL/IOWIN.
+//! The Local APIC base comes from the IA32_APIC_BASE MSR. The I/O APIC base
+//! and the ISA interrupt source overrides come from the ACPI MADT when
+//! `acpi::init()` has run, with the conventional 0xFEC0_0000 and identity ISA
+//! routing as fallbacks. The I/O APIC uses indirect register access via
+//! IOREGSEL/IOWIN.
 //!
 //! Register constants and hardware API methods define the complete Local APIC
 //! and I/O APIC register set per the Intel SDM. Unused items are retained for
@@ -104,7 +106,7 @@ const TIMER_MODE_PERIODIC: u32 = 0b01 << 17;
 // I/O APIC
 // ---------------------------------------------------------------------------
 
-/// Default I/O APIC MMIO base address (QEMU virt machine).
+/// I/O APIC MMIO base address used when ACPI provides no MADT.
 const IOAPIC_BASE: usize = 0xFEC0_0000;
 
 /// I/O APIC Register Select (write the register index here).
@@ -385,8 +387,8 @@ impl LocalApic {
 /// The I/O APIC uses indirect register access: write the register index to
 /// IOREGSEL, then read/write the value through IOWIN.
 pub struct IoApic {
-    /// Virtual address of the I/O APIC MMIO base (identity-mapped at
-    /// 0xFEC0_0000).
+    /// Virtual address of the I/O APIC MMIO base (MADT address, or
+    /// 0xFEC0_0000 without ACPI).
     base: usize,
 }
 
@@ -399,8 +401,8 @@ impl IoApic {
     /// Read a 32-bit I/O APIC register.
     pub fn read_register(&self, reg: u32) -> u32 {
         // SAFETY: IOREGSEL at base+0x00 and IOWIN at base+0x10 are the I/O
-        // APIC's indirect register access ports. The base address 0xFEC0_0000
-        // is identity-mapped by the bootloader. Volatile writes ensure the
+        // APIC's indirect register access ports. The base address is mapped
+        // through the bootloader's physical memory offset. Volatile writes ensure the
         // register select is visible to hardware before the window read.
         unsafe {
             ptr::write_volatile((self.base + IOREGSEL as usize) as *mut u32, reg);
@@ -482,6 +484,31 @@ impl IoApic {
 struct ApicState {
     local_apic: LocalApic,
     io_apic: IoApic,
+    /// First GSI handled by `io_apic` (from the MADT, 0 without ACPI).
+    io_apic_gsi_base: u32,
+}
+
+impl ApicState {
+    /// Translate an IRQ number to an I/O APIC pin and its polarity/trigger.
+    ///
+    /// ISA IRQs (0-15) go through the MADT interrupt source overrides, so
+    /// e.g. the PIT on IRQ 0 lands on GSI 2 on most chipsets. Returns
+    /// `(pin, active_low, level_triggered)`.
+    fn irq_to_pin(&self, irq: u8) -> KernelResult<(u8, bool, bool)> {
+        let (gsi, active_low, level) = if irq < 16 {
+            super::acpi::with_acpi_info(|info| info.irq_to_gsi(irq))
+                .unwrap_or((irq as u32, false, false))
+        } else {
+            (irq as u32, true, true)
+        };
+        gsi.checked_sub(self.io_apic_gsi_base)
+            .filter(|&pin| pin < self.io_apic.max_redirection_entries() as u32)
+            .map(|pin| (pin as u8, active_low, level))
+            .ok_or(KernelError::InvalidArgument {
+                name: "irq",
+                value: "not routed to the I/O APIC",
+            })
+    }
 }
 
 // SAFETY: ApicState contains only raw pointer-like fields (usize base
@@ -504,7 +531,8 @@ use super::msr::{phys_to_virt, rdmsr, wrmsr};
 /// Initialize the Local APIC and I/O APIC.
 ///
 /// This function:
-/// 1. Reads the APIC base address from the IA32_APIC_BASE MSR.
+/// 1. Reads the APIC base address from the IA32_APIC_BASE MSR and the I/O APIC
+///    base from the MADT (if ACPI tables were parsed).
 /// 2. Ensures the global APIC enable bit is set in the MSR.
 /// 3. Translates physical MMIO addresses to virtual using the bootloader's
 ///    physical memory offset.
@@ -537,7 +565,30 @@ pub fn init() -> KernelResult<()> {
     let lapic_virt = phys_to_virt(apic_base_phys).ok_or(KernelError::NotInitialized {
         subsystem: "physical memory mapping (APIC)",
     })?;
-    let ioapic_virt = phys_to_virt(IOAPIC_BASE).ok_or(KernelError::NotInitialized {
+    // Take the I/O APIC serving GSI 0 from the MADT; without ACPI fall back
+    // to the conventional address.
+    let (ioapic_phys, ioapic_gsi_base, madt_lapic) = super::acpi::with_acpi_info(|info| {
+        let ioapic = info
+            .io_apic_for_gsi(0)
+            .map_or((IOAPIC_BASE, 0), |a| (a.address as usize, a.gsi_base));
+        (
+            ioapic.0,
+            ioapic.1,
+            info.has_madt.then_some(info.local_apic_address),
+        )
+    })
+    .unwrap_or((IOAPIC_BASE, 0, None));
+
+    if let Some(madt_lapic) = madt_lapic {
+        if madt_lapic as usize != apic_base_phys {
+            println!(
+                "[APIC] WARNING: MADT LAPIC address {:#x} differs from MSR, using MSR",
+                madt_lapic
+            );
+        }
+    }
+
+    let ioapic_virt = phys_to_virt(ioapic_phys).ok_or(KernelError::NotInitialized {
         subsystem: "physical memory mapping (I/O APIC)",
     })?;
 
@@ -579,8 +630,8 @@ pub fn init() -> KernelResult<()> {
 
     let max_irqs = ioapic.max_redirection_entries();
     println!(
-        "[APIC] I/O APIC initialized at {:#x} ({} IRQ lines)",
-        ioapic_virt, max_irqs
+        "[APIC] I/O APIC initialized at phys {:#x} ({} IRQ lines, GSI base {})",
+        ioapic_phys, max_irqs, ioapic_gsi_base
     );
 
     // Store global state.
@@ -588,6 +639,7 @@ pub fn init() -> KernelResult<()> {
     *state = Some(ApicState {
         local_apic: lapic,
         io_apic: ioapic,
+        io_apic_gsi_base: ioapic_gsi_base,
     });
     APIC_INITIALIZED.store(true, Ordering::Release);
 
@@ -659,38 +711,45 @@ pub fn stop_timer() -> KernelResult<()> {
 /// Route an external IRQ through the I/O APIC to a specific interrupt vector
 /// and destination CPU.
 ///
-/// - `irq`: I/O APIC input pin (0-23 typically).
+/// - `irq`: ISA IRQ (0-15, remapped through the MADT interrupt source
+///   overrides) or GSI (16 and up, level-triggered active-low as for PCI).
 /// - `vector`: IDT vector number.
 /// - `dest`: Destination Local APIC ID.
 pub fn set_irq_route(irq: u8, vector: u8, dest: u8) -> KernelResult<()> {
     let state = APIC_STATE.lock();
     match state.as_ref() {
         Some(s) => {
-            s.io_apic.set_irq_route(irq, vector, dest);
+            let (pin, active_low, level) = s.irq_to_pin(irq)?;
+            let mut entry = RedirectionEntry::new(vector);
+            entry.set_destination(dest);
+            entry.set_active_low(active_low);
+            entry.set_level_triggered(level);
+            entry.set_masked(false);
+            s.io_apic.write_redirection(pin, entry);
             Ok(())
         }
         None => Err(KernelError::NotInitialized { subsystem: "APIC" }),
     }
 }
 
-/// Mask an IRQ in the I/O APIC.
+/// Mask an IRQ in the I/O APIC (numbered as for [`set_irq_route`]).
 pub fn mask_irq(irq: u8) -> KernelResult<()> {
     let state = APIC_STATE.lock();
     match state.as_ref() {
         Some(s) => {
-            s.io_apic.mask_irq(irq);
+            s.io_apic.mask_irq(s.irq_to_pin(irq)?.0);
             Ok(())
         }
         None => Err(KernelError::NotInitialized { subsystem: "APIC" }),
     }
 }
 
-/// Unmask an IRQ in the I/O APIC.
+/// Unmask an IRQ in the I/O APIC (numbered as for [`set_irq_route`]).
 pub fn unmask_irq(irq: u8) -> KernelResult<()> {
     let state = APIC_STATE.lock();
     match state.as_ref() {
         Some(s) => {
-            s.io_apic.unmask_irq(irq);
+            s.io_apic.unmask_irq(s.irq_to_pin(irq)?.0);
             Ok(())
         }
         None => Err(KernelError::NotInitialized { subsystem: "APIC" }),
@@ -97,6 +97,25 @@ pub fn get_framebuffer_info() -> Option<BootFramebufferInfo> {
     })
 }
 
+/// The ramdisk (initramfs image) loaded by the bootloader, if any.
+///
+/// The bootloader maps the image into the kernel address space and marks its
+/// frames as in use, so the slice stays valid for the kernel lifetime.
+pub fn ramdisk() -> Option<&'static [u8]> {
+    // SAFETY: BOOT_INFO is a static mut written once during early boot
+    // (in the entry_point! callback) and read-only afterwards.
+    #[allow(static_mut_refs)]
+    let boot_info = unsafe { BOOT_INFO.as_ref()? };
+    let addr = boot_info.ramdisk_addr.into_option()?;
+    let len = boot_info.ramdisk_len as usize;
+    if len == 0 {
+        return None;
+    }
+    // SAFETY: The bootloader mapped `len` bytes at `addr` for the kernel
+    // and never reuses those frames.
+    Some(unsafe { core::slice::from_raw_parts(addr as *const u8, len) })
+}
+
 // I/O port functions: delegate to the canonical implementations in
 // the parent module (arch/x86_64/mod.rs) to avoid duplication.
 use super::{inb, outb};
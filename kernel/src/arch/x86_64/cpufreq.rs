@@ -415,6 +415,26 @@ pub fn cpufreq_set_frequency(khz: u64) -> KernelResult<()> {
     Ok(())
 }
 
+/// Like [`cpufreq_set_frequency`], but gives up instead of spinning when the
+/// driver state is locked, so the governor can call it from the timer
+/// interrupt. Returns whether the frequency was set.
+pub fn cpufreq_try_set_frequency(khz: u64) -> bool {
+    if !CPUFREQ_INITIALIZED.load(Ordering::Acquire) {
+        return false;
+    }
+    let Some(info) = CPUFREQ_INFO.try_lock() else {
+        return false;
+    };
+    if !info.pstate_available || info.bus_clock_khz == 0 {
+        return false;
+    }
+
+    let ratio = ((khz / info.bus_clock_khz) as u8).clamp(info.min_ratio, info.max_ratio);
+    set_pstate_ratio(&info, ratio);
+    CURRENT_FREQ_KHZ.store(info.ratio_to_khz(ratio), Ordering::Release);
+    true
+}
+
 /// Get available CPU frequencies in kHz.
 ///
 /// Returns frequencies for each P-state ratio from min to max.
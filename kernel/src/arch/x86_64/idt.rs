@@ -27,6 +27,13 @@ lazy_static! {
         idt[32].set_handler_fn(timer_interrupt_handler);
         // Add keyboard interrupt handler (IRQ1 = interrupt 33)
         idt[33].set_handler_fn(keyboard_interrupt_handler);
+        // Serial ports: IRQ3 (COM2/COM4) and IRQ4 (COM1/COM3), routed
+        // through the I/O APIC by the serial driver
+        idt[35].set_handler_fn(serial_interrupt_handler);
+        idt[36].set_handler_fn(serial_interrupt_handler);
+        // Intel e1000 NICs, by MSI or through the I/O APIC
+        #[cfg(feature = "net")]
+        idt[crate::drivers::e1000::NIC_VECTOR].set_handler_fn(nic_interrupt_handler);
         // Add APIC timer interrupt handler (vector 48, separate from PIC timer at 32)
         idt[48].set_handler_fn(apic_timer_interrupt_handler);
         // Add TLB shootdown IPI handler (vector 49)
@@ -41,7 +48,37 @@ pub fn init() {
     IDT.load();
 }
 
+/// Keeps GS on the per-CPU area while an interrupt handler runs.
+///
+/// GS_BASE and KERNEL_GS_BASE both hold the area (see `sched::percpu`), so
+/// this is a no-op unless the interrupted user code loaded its own GS
+/// selector. In that case `swapgs` brings the area back, and dropping the
+/// guard swaps the user's GS back in before the handler returns.
+struct KernelGs(bool);
+
+impl KernelGs {
+    fn enter(frame: &InterruptStackFrame) -> Self {
+        let from_user = frame.code_segment.0 & 3 == 3;
+        if from_user {
+            // SAFETY: Coming from ring 3, KERNEL_GS_BASE holds the per-CPU
+            // area. The guard performs the matching swapgs on drop.
+            unsafe { core::arch::asm!("swapgs", options(nomem, nostack, preserves_flags)) };
+        }
+        Self(from_user)
+    }
+}
+
+impl Drop for KernelGs {
+    fn drop(&mut self) {
+        if self.0 {
+            // SAFETY: Undoes the swapgs in KernelGs::enter.
+            unsafe { core::arch::asm!("swapgs", options(nomem, nostack, preserves_flags)) };
+        }
+    }
+}
+
 extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
+    let _gs = KernelGs::enter(&stack_frame);
     println!("EXCEPTION: BREAKPOINT\n{:#?}", stack_frame);
 }
 
@@ -79,15 +116,11 @@ extern "x86-interrupt" fn double_fault_handler(
 }
 
 extern "x86-interrupt" fn page_fault_handler(
-    stack_frame: InterruptStackFrame,
+    mut stack_frame: InterruptStackFrame,
     error_code: PageFaultErrorCode,
 ) {
+    let _gs = KernelGs::enter(&stack_frame);
     crate::perf::count_page_fault();
-    crate::trace!(
-        crate::perf::trace::TraceEventType::PageFault,
-        0, // CR2 not yet read; filled in after read below
-        error_code.bits()
-    );
 
     // SAFETY: Read CR2 (faulting address) before any code that might trigger
     // another page fault, which would overwrite CR2.
@@ -96,6 +129,11 @@ extern "x86-interrupt" fn page_fault_handler(
         core::arch::asm!("mov {}, cr2", out(reg) val, options(nomem, nostack));
         val
     };
+    crate::trace!(
+        crate::perf::trace::TraceEventType::PageFault,
+        cr2_val,
+        error_code.bits()
+    );
 
     let ec = error_code.bits();
     let rip_val = stack_frame.instruction_pointer.as_u64();
@@ -131,6 +169,25 @@ extern "x86-interrupt" fn page_fault_handler(
         }
     }
 
+    // A kernel fault inside the user-copy routine means a syscall was handed
+    // a bad user pointer: resume at the routine's fault exit so the syscall
+    // fails with EFAULT instead of taking the kernel down.
+    if !was_user {
+        if let Some(landing) = crate::arch::x86_64::uaccess::fixup(rip_val) {
+            // SAFETY: The x86-interrupt ABI passes the saved frame in place
+            // and RIP is its first word (see the GP handler below). The
+            // landing code is the tail of the same leaf routine, so the
+            // interrupted stack and registers stay consistent.
+            unsafe {
+                core::ptr::write_volatile(
+                    &mut stack_frame as *mut InterruptStackFrame as *mut u64,
+                    landing,
+                );
+            }
+            return;
+        }
+    }
+
     // Unresolvable fault — print diagnostics via raw serial, then halt or
     // kill the process.
     // SAFETY: Writing to COM1 data register at I/O port 0x3F8 for diagnostics.
@@ -187,6 +244,15 @@ extern "x86-interrupt" fn page_fault_handler(
             }
         }
 
+        // SAFETY: As above; the walk stays within the mapped user stack.
+        unsafe {
+            raw_user_backtrace(
+                rip_val,
+                interrupted_frame_pointer(),
+                stack_frame.stack_pointer.as_u64(),
+            );
+        }
+
         // Mark process as Zombie before returning to boot context.
         // Only use atomic state operations (set_exit_code, set_state).
         // Do NOT iterate threads BTreeMap or look up parent via
@@ -215,15 +281,13 @@ extern "x86-interrupt" fn page_fault_handler(
             x86_64::instructions::hlt();
         }
     } else {
-        // Kernel fault — unrecoverable. Print and halt.
-        println!(
-            "FATAL: kernel page fault at {:#x} ec={:#x} rip={:#x}",
+        // Kernel fault — unrecoverable. Panic so a crash dump is taken.
+        println!("{:#?}", stack_frame);
+        crate::crash::set_exception(exception_frame(&stack_frame, ec, cr2_val));
+        panic!(
+            "kernel page fault at {:#x} ec={:#x} rip={:#x}",
             cr2_val, ec, rip_val
         );
-        println!("{:#?}", stack_frame);
-        loop {
-            x86_64::instructions::hlt();
-        }
     }
 }
 
@@ -262,11 +326,112 @@ extern "x86-interrupt" fn general_protection_fault_handler(
         raw_serial_str(b"\n");
     }
 
+    // A fault in kernel code is a kernel bug: panic so a crash dump is taken.
+    if stack_frame.code_segment.0 & 3 == 0 {
+        crate::crash::set_exception(exception_frame(&stack_frame, error_code, 0));
+        panic!(
+            "general protection fault err={:#x} rip={:#x}",
+            error_code,
+            stack_frame.instruction_pointer.as_u64()
+        );
+    }
+
+    // SAFETY: A user fault runs on the user's address space; COM1 output.
+    unsafe {
+        raw_user_backtrace(
+            stack_frame.instruction_pointer.as_u64(),
+            interrupted_frame_pointer(),
+            stack_frame.stack_pointer.as_u64(),
+        );
+    }
+
     loop {
         x86_64::instructions::hlt();
     }
 }
 
+/// Print the backtrace of a faulting user thread via raw serial.
+///
+/// Walks the user's frame-pointer chain (only programs built with frame
+/// pointers get more than the faulting frame) and names frames from the
+/// executable's symbols if their lock is free.
+///
+/// # Safety
+/// The user address space must be the current one. Port 0x3F8 must be a
+/// valid COM1 data register.
+unsafe fn raw_user_backtrace(rip: u64, rbp: u64, rsp: u64) {
+    const USER_TOP: u64 = 0x0000_8000_0000_0000;
+
+    let process = crate::process::current_process();
+    let symbols = process.and_then(|p| p.symbols.try_lock().and_then(|s| s.clone()));
+    let mut index = 0u64;
+    let mut print_frame = |addr: u64, lookup: u64| {
+        raw_serial_str(b"  #");
+        raw_serial_hex(index);
+        raw_serial_str(b" 0x");
+        raw_serial_hex(addr);
+        match symbols.as_ref().and_then(|s| s.lookup(lookup)) {
+            Some((name, offset)) => {
+                raw_serial_str(b" ");
+                raw_serial_str(name.as_bytes());
+                raw_serial_str(b"+0x");
+                raw_serial_hex(offset + addr - lookup);
+            }
+            None => raw_serial_str(b" ?"),
+        }
+        raw_serial_str(b"\n");
+        index += 1;
+    };
+
+    raw_serial_str(b"  backtrace:\n");
+    print_frame(rip, rip);
+    if rsp > 0x1000 && rbp < USER_TOP && rsp < USER_TOP {
+        // The call instruction is the byte before a return address.
+        crate::crash::backtrace::walk(rbp, rsp, |ret| {
+            if ret < USER_TOP {
+                print_frame(ret, ret.wrapping_sub(1));
+            }
+        });
+    }
+}
+
+/// Frame pointer of the code an exception interrupted.
+///
+/// Exception handlers are built with frame pointers, and the CPU does not
+/// touch rbp on entry, so the handler's frame links straight back to the
+/// interrupted function's frame. Must be inlined into the handler itself.
+#[inline(always)]
+fn interrupted_frame_pointer() -> u64 {
+    let rbp: u64;
+    // SAFETY: Reads rbp only.
+    unsafe {
+        core::arch::asm!("mov {}, rbp", out(reg) rbp, options(nomem, nostack, preserves_flags));
+    }
+    if rbp == 0 || !rbp.is_multiple_of(8) {
+        return 0;
+    }
+    // SAFETY: With frame pointers, rbp points at the saved caller rbp on the
+    // handler's own stack.
+    unsafe { core::ptr::read_volatile(rbp as *const u64) }
+}
+
+/// The interrupted context of a fatal exception, for the crash dump.
+#[inline(always)]
+fn exception_frame(
+    stack_frame: &InterruptStackFrame,
+    error_code: u64,
+    fault_addr: u64,
+) -> crate::crash::ExceptionFrame {
+    crate::crash::ExceptionFrame {
+        ip: stack_frame.instruction_pointer.as_u64(),
+        sp: stack_frame.stack_pointer.as_u64(),
+        fp: interrupted_frame_pointer(),
+        flags: stack_frame.cpu_flags.bits(),
+        error_code,
+        fault_addr,
+    }
+}
+
 /// Write a byte string to COM1 serial, bypassing all locks.
 ///
 /// # Safety
@@ -295,7 +460,8 @@ pub(crate) unsafe fn raw_serial_hex(val: u64) {
     }
 }
 
-extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
+extern "x86-interrupt" fn timer_interrupt_handler(stack_frame: InterruptStackFrame) {
+    let _gs = KernelGs::enter(&stack_frame);
     crate::perf::count_interrupt();
 
     // Notify the scheduler of a timer tick for preemptive scheduling.
@@ -316,9 +482,14 @@ extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFr
     }
 }
 
-extern "x86-interrupt" fn apic_timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
+extern "x86-interrupt" fn apic_timer_interrupt_handler(stack_frame: InterruptStackFrame) {
+    let _gs = KernelGs::enter(&stack_frame);
     crate::perf::count_interrupt();
 
+    // Charge the elapsed interval to the interrupted thread (user time if
+    // we came from ring 3) and run the ITIMER_* countdowns.
+    crate::process::itimer::account_tick(stack_frame.code_segment.0 & 3 == 3);
+
     // Increment the global tick counter (atomic, always safe from interrupt
     // context).
     super::timer::tick();
@@ -342,7 +513,29 @@ extern "x86-interrupt" fn sched_wake_handler(_stack_frame: InterruptStackFrame)
     crate::arch::x86_64::apic::send_eoi();
 }
 
-extern "x86-interrupt" fn keyboard_interrupt_handler(_stack_frame: InterruptStackFrame) {
+extern "x86-interrupt" fn serial_interrupt_handler(stack_frame: InterruptStackFrame) {
+    let _gs = KernelGs::enter(&stack_frame);
+    crate::perf::count_interrupt();
+
+    // Moves received bytes into the port ring buffers; only try-locks, so
+    // it is safe against interrupted serial output.
+    crate::drivers::serial::handle_interrupt();
+    // Routed through the I/O APIC, so the EOI goes to the Local APIC.
+    crate::arch::x86_64::apic::send_eoi();
+}
+
+#[cfg(feature = "net")]
+extern "x86-interrupt" fn nic_interrupt_handler(stack_frame: InterruptStackFrame) {
+    let _gs = KernelGs::enter(&stack_frame);
+    crate::perf::count_interrupt();
+
+    // Acknowledges the NICs and queues received frames; only try-locks.
+    crate::drivers::e1000::handle_interrupt();
+    crate::arch::x86_64::apic::send_eoi();
+}
+
+extern "x86-interrupt" fn keyboard_interrupt_handler(stack_frame: InterruptStackFrame) {
+    let _gs = KernelGs::enter(&stack_frame);
     crate::perf::count_interrupt();
 
     // Read scancode from PS/2 data port (0x60) and forward to keyboard driver.
@@ -25,6 +25,7 @@ pub mod rtc;
 pub mod serial;
 pub mod syscall;
 pub mod timer;
+pub mod uaccess;
 pub mod usermode;
 pub mod vga;
 
@@ -97,6 +98,16 @@ pub fn init() {
     mmu::init();
     println!("[ARCH] MMU initialized");
 
+    // Parse ACPI tables (MADT, MCFG, FADT) from UEFI firmware for hardware
+    // topology. Done before APIC init so the I/O APIC address and ISA
+    // overrides come from the MADT. Non-fatal: falls back to defaults if
+    // RSDP unavailable.
+    println!("[ARCH] Parsing ACPI tables...");
+    match acpi::init() {
+        Ok(()) => println!("[ARCH] ACPI tables parsed"),
+        Err(e) => println!("[ARCH] ACPI init skipped: {}", e),
+    }
+
     // Initialize Local APIC + I/O APIC (additive to PIC -- PIC remains as
     // fallback). APIC init is non-fatal: if it fails the kernel continues
     // with PIC-only interrupt routing.
@@ -106,14 +117,6 @@ pub fn init() {
         Err(e) => println!("[ARCH] APIC init skipped: {}", e),
     }
 
-    // Parse ACPI tables (MADT, MCFG) from UEFI firmware for hardware topology.
-    // Non-fatal: falls back to defaults if RSDP unavailable.
-    println!("[ARCH] Parsing ACPI tables...");
-    match acpi::init() {
-        Ok(()) => println!("[ARCH] ACPI tables parsed"),
-        Err(e) => println!("[ARCH] ACPI init skipped: {}", e),
-    }
-
     // Calibrate and start the APIC timer for preemptive scheduling.
     // Requires APIC to be initialized. Non-fatal: falls back to PIC timer
     // (which must be explicitly enabled elsewhere) if calibration fails.
@@ -193,8 +196,15 @@ pub fn disable_interrupts() -> impl Drop {
         }
     }
 
-    let was_enabled = x86_64::instructions::interrupts::are_enabled();
-    x86_64::instructions::interrupts::disable();
+    // Host unit tests run in user mode, where cli faults
+    #[cfg(target_os = "none")]
+    let was_enabled = {
+        let enabled = x86_64::instructions::interrupts::are_enabled();
+        x86_64::instructions::interrupts::disable();
+        enabled
+    };
+    #[cfg(not(target_os = "none"))]
+    let was_enabled = false;
     InterruptGuard { was_enabled }
 }
 
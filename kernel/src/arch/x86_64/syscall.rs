@@ -4,15 +4,11 @@
 //! transitions. The key components are:
 //! - `syscall_entry`: naked assembly handler invoked by the SYSCALL instruction
 //! - `PerCpuData`: per-CPU storage for kernel/user RSP, accessed via GS segment
-//! - `init_syscall`: MSR configuration (EFER, STAR, LSTAR, SFMASK,
-//!   KernelGsBase)
+//! - `init_syscall`: MSR configuration (EFER, STAR, LSTAR, SFMASK)
 
 #![allow(function_casts_as_integer)]
 
-use core::{
-    cell::UnsafeCell,
-    sync::atomic::{AtomicU64, Ordering},
-};
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use crate::syscall::syscall_handler;
 
@@ -72,49 +68,29 @@ pub fn get_syscall_frame() -> Option<&'static SyscallFrame> {
 ///
 /// Only valid during syscall handler execution.
 pub fn get_saved_user_rsp() -> u64 {
-    // SAFETY: PER_CPU_AREA.user_rsp is set by syscall_entry (mov gs:[0x8], rsp)
-    // before switching to the kernel stack. It is valid during syscall handling.
-    unsafe { (*PER_CPU_AREA.0.get()).user_rsp }
+    // SAFETY: user_rsp is set by syscall_entry (mov gs:[0x8], rsp) before
+    // switching to the kernel stack. It is valid during syscall handling.
+    unsafe { (*per_cpu_data_ptr()).user_rsp }
 }
 
 /// Per-CPU data accessed via GS segment register during syscall entry/exit.
 ///
 /// The `syscall_entry` naked asm reads `kernel_rsp` from `gs:[0x0]` and saves
-/// `user_rsp` to `gs:[0x8]`. This struct must be `#[repr(C)]` to guarantee
-/// field layout matches the assembly offsets.
-#[repr(C)]
-pub struct PerCpuData {
-    /// Kernel stack pointer (offset 0x0) -- loaded into RSP on syscall entry
-    pub kernel_rsp: u64,
-    /// User stack pointer (offset 0x8) -- saved from RSP on syscall entry
-    pub user_rsp: u64,
-}
-
-#[repr(transparent)]
-pub(super) struct PerCpuDataCell(UnsafeCell<PerCpuData>);
-
-// SAFETY: Per-CPU data is only accessed via GS register from the current CPU
-// during syscall entry/exit. On a single-CPU system (our current QEMU config),
-// there are no concurrent accesses. The naked asm in syscall_entry uses
-// `mov gs:[offset]` which does not go through Rust's aliasing rules.
-unsafe impl Sync for PerCpuDataCell {}
-
-pub(super) static PER_CPU_AREA: PerCpuDataCell = PerCpuDataCell(UnsafeCell::new(PerCpuData {
-    kernel_rsp: 0,
-    user_rsp: 0,
-}));
+/// `user_rsp` to `gs:[0x8]`. These are the first fields of the per-CPU area
+/// that GS points at; see [`crate::sched::percpu`].
+pub use crate::sched::percpu::CpuArea as PerCpuData;
 
 // CR3 switching removed: Process page tables now contain complete kernel
 // mapping (L4 entries 256-511 copied from boot tables), so syscalls run
 // with user CR3 active. This eliminates the GP fault on CR3 restore that
 // occurred when switching back to incompatible user page tables.
 
-/// Get a mutable pointer to the per-CPU data.
+/// Get a mutable pointer to the current CPU's per-CPU data.
 ///
-/// Used to update `kernel_rsp` on context switch and to set up KernelGsBase
-/// during init. The returned pointer is valid for the lifetime of the kernel.
+/// Used to update `kernel_rsp` on context switch. The returned pointer is
+/// valid for the lifetime of the kernel.
 pub fn per_cpu_data_ptr() -> *mut PerCpuData {
-    PER_CPU_AREA.0.get()
+    crate::sched::percpu::this_area()
 }
 
 /// x86_64 SYSCALL instruction entry point
@@ -219,12 +195,14 @@ pub unsafe extern "C" fn syscall_entry() {
 /// - **LSTAR**: Set syscall entry point to `syscall_entry`
 /// - **STAR**: Set segment selectors for SYSCALL (kernel) and SYSRET (user)
 /// - **SFMASK**: Mask IF flag so syscall entry runs with interrupts disabled
-/// - **KernelGsBase**: Point to `PerCpuData` for swapgs in syscall_entry
+///
+/// `KernelGsBase`, which `swapgs` in syscall_entry loads into GS, is set
+/// up by `percpu::init_cpu` before this runs.
 ///
 /// Must be called after `gdt::init()` and before any user-mode transitions.
 pub fn init_syscall() {
     use x86_64::registers::{
-        model_specific::{Efer, EferFlags, KernelGsBase, LStar, SFMask, Star},
+        model_specific::{Efer, EferFlags, LStar, SFMask, Star},
         rflags::RFlags,
     };
 
@@ -233,8 +211,8 @@ pub fn init_syscall() {
     let sels = gdt::selectors();
 
     // SAFETY: Writing MSRs to configure SYSCALL/SYSRET is required during
-    // kernel init for system call support. EFER, LSTAR, STAR, SFMASK, and
-    // KernelGsBase are x86_64 model-specific registers that control the
+    // kernel init for system call support. EFER, LSTAR, STAR and SFMASK
+    // are x86_64 model-specific registers that control the
     // SYSCALL instruction behavior. This is called with interrupts disabled
     // during single-threaded init.
     unsafe {
@@ -277,15 +255,12 @@ pub fn init_syscall() {
     // switched to the kernel stack.
     SFMask::write(RFlags::INTERRUPT_FLAG);
 
-    // Set up per-CPU data for swapgs.
-    // KernelGsBase is swapped with GsBase on the `swapgs` instruction.
-    // After swapgs in syscall_entry, GS points to our PerCpuData so the
-    // assembly can read kernel_rsp from gs:[0x0] and save user_rsp to gs:[0x8].
+    // Per-CPU data for swapgs: percpu::init_cpu points both GsBase and
+    // KernelGsBase at this CPU's PerCpuData, so after swapgs in syscall_entry
+    // the assembly can read kernel_rsp from gs:[0x0] and save user_rsp to
+    // gs:[0x8].
     //
     // CR3 initialization removed: Process page tables now contain complete
     // kernel mappings (L4 entries 256-511), so syscalls run with user CR3
     // and can directly access kernel data structures.
-
-    let per_cpu_addr = per_cpu_data_ptr() as u64;
-    KernelGsBase::write(x86_64::VirtAddr::new(per_cpu_addr));
 }
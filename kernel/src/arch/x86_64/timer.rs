@@ -12,12 +12,16 @@ pub fn get_ticks() -> u64 {
 /// Increment timer ticks (called from APIC timer interrupt handler at vector
 /// 48).
 ///
-/// Increments the global tick counter and triggers a scheduler tick for
-/// preemptive scheduling. Uses `try_lock()` on the scheduler to avoid
-/// deadlock if the scheduler lock is already held (e.g., we interrupted
-/// mid-schedule).
+/// Increments the global tick counter, checks and kicks the watchdogs,
+/// triggers a scheduler tick for preemptive scheduling, runs the periodic
+/// power management work (cpufreq governor, thermal and battery polling),
+/// advances the software vblank, tops up the audio output device and drains
+/// the serial ports. Uses
+/// `try_lock()` on the scheduler to avoid deadlock if the scheduler lock is
+/// already held (e.g., we interrupted mid-schedule).
 pub fn tick() {
     TICKS.fetch_add(1, Ordering::Relaxed);
+    crate::drivers::watchdog::check();
 
     // Trigger scheduler tick. Use try_lock to avoid deadlock: if the
     // scheduler lock is already held (e.g., we interrupted mid-schedule),
@@ -25,6 +29,10 @@ pub fn tick() {
     if let Some(mut sched) = crate::sched::scheduler::current_scheduler().try_lock() {
         sched.tick();
     }
+    crate::power::timer_tick();
+    crate::graphics::vsync_sw::vblank_tick();
+    crate::audio::pipeline::pump();
+    crate::drivers::serial::poll();
 }
 
 /// Setup timer for periodic interrupts
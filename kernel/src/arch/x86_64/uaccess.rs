@@ -0,0 +1,57 @@
+//! Fault-tolerant user memory copy for x86_64.
+//!
+//! [`copy`] moves bytes with a single `rep movsb`. If that instruction takes
+//! a page fault the page fault handler cannot resolve, the handler asks
+//! [`fixup`] for a landing address and resumes there instead of panicking;
+//! the landing code returns the number of bytes left uncopied (RCX at the
+//! time of the fault). This lets `syscall::uaccess` turn a user pointer that
+//! passed validation but is not actually backed (e.g. unmapped by another
+//! thread in the meantime) into `EFAULT`.
+
+use core::arch::global_asm;
+
+global_asm!(
+    r#"
+.section .text.uaccess, "ax"
+.global __uaccess_copy
+// rdi = destination, rsi = source, rdx = length
+// returns rax = bytes not copied (0 on success)
+__uaccess_copy:
+    mov rcx, rdx
+.global __uaccess_copy_insn
+__uaccess_copy_insn:
+    rep movsb
+    xor eax, eax
+    ret
+.global __uaccess_copy_fault
+__uaccess_copy_fault:
+    mov rax, rcx
+    ret
+"#
+);
+
+extern "C" {
+    fn __uaccess_copy(dst: *mut u8, src: *const u8, len: usize) -> usize;
+    static __uaccess_copy_insn: u8;
+    static __uaccess_copy_fault: u8;
+}
+
+/// Copy `len` bytes from `src` to `dst`, returning how many bytes could not
+/// be copied because of an unresolvable page fault.
+///
+/// # Safety
+///
+/// Kernel-side memory in the range must be valid. User-side memory may be
+/// unmapped; faults on it end the copy early instead of crashing.
+pub unsafe fn copy(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    // SAFETY: Forwarded to the caller; the routine follows the SysV ABI.
+    unsafe { __uaccess_copy(dst, src, len) }
+}
+
+/// Landing address for a kernel-mode fault at `rip`, if it happened inside
+/// [`copy`].
+pub fn fixup(rip: u64) -> Option<u64> {
+    let insn = core::ptr::addr_of!(__uaccess_copy_insn) as u64;
+    let fault = core::ptr::addr_of!(__uaccess_copy_fault) as u64;
+    (rip == insn).then_some(fault)
+}
@@ -56,7 +56,8 @@ const BOOT_CANARY_MAGIC: u64 = 0xDEAD_BEEF_CAFE_BABE;
 pub unsafe fn enter_usermode(entry_point: u64, user_stack: u64, user_cs: u64, user_ss: u64) -> ! {
     // SAFETY: We build the iretq frame on the current kernel stack.
     // iretq expects (from top of stack): RIP, CS, RFLAGS, RSP, SS.
-    // We set DS and ES to the user data selector and clear FS/GS.
+    // We set DS and ES to the user data selector and clear FS. GS is left
+    // alone: its base is the per-CPU area (see sched::percpu).
     // RFLAGS = 0x202: bit 1 (reserved, always 1) + bit 9 (IF = interrupts enabled).
     // The caller guarantees all arguments point to valid mapped memory and
     // the GDT/TSS/per-CPU data are properly configured.
@@ -75,9 +76,9 @@ pub unsafe fn enter_usermode(entry_point: u64, user_stack: u64, user_cs: u64, us
         // Set data segment registers to user data selector
         "mov ds, {ss:r}",
         "mov es, {ss:r}",
-        // Clear FS and GS. Writing 0 to FS zeros FS_BASE (MSR 0xC0000100).
+        // Clear FS. Writing 0 to FS zeros FS_BASE (MSR 0xC0000100). GS is
+        // not reloaded, which would zero the per-CPU GS_BASE the same way.
         "mov fs, {zero:x}",
-        "mov gs, {zero:x}",
         // Build iretq frame on current kernel stack FIRST, before wrmsr.
         // wrmsr uses ECX (MSR number), EDX:EAX (value) as IMPLICIT operands.
         // All iretq frame values must be pushed BEFORE wrmsr, because wrmsr
@@ -201,8 +202,7 @@ pub unsafe extern "C" fn enter_usermode_returnable(
         "mov ds, ecx",
         "mov es, ecx",
         "xor eax, eax",
-        "mov fs, ax",
-        "mov gs, ax",
+        "mov fs, ax",                 // GS keeps the per-CPU base
 
         // Build iretq frame on stack
         "push rcx",       // SS
@@ -303,8 +303,7 @@ pub unsafe extern "C" fn enter_forked_child_returnable(
         "mov ds, ax",
         "mov es, ax",
         "xor eax, eax",
-        "mov fs, ax",
-        "mov gs, ax",
+        "mov fs, ax",                   // GS keeps the per-CPU base
 
         // ---- build iretq frame from ForkChildRegs ----
         "push 0x2B",                    // SS  (user data segment)
@@ -344,7 +343,7 @@ pub unsafe extern "C" fn enter_forked_child_returnable(
 ///
 /// Called from `sys_exit` after cleaning up the exiting process. This function:
 /// 1. Restores the boot CR3 (switching back to boot page tables)
-/// 2. Restores kernel segment registers (DS, ES, FS, GS cleared)
+/// 2. Restores kernel segment registers (DS, ES, FS cleared)
 /// 3. Does `swapgs` to balance the swapgs from `syscall_entry`
 /// 4. Restores RSP to the saved value (past the callee-saved pushes)
 /// 5. Pops callee-saved registers and returns to the caller of
@@ -424,8 +423,8 @@ pub unsafe fn boot_return_to_kernel() -> ! {
     // mode. rsp points to the stack with 8 bytes of alignment padding and
     // 6 callee-saved registers, with the return address below them. We
     // restore kernel segment registers and
-    // balance the swapgs from syscall_entry. The swapgs must come BEFORE
-    // clearing GS so we don't corrupt KERNEL_GS_BASE. After restoring RSP
+    // balance the swapgs from syscall_entry, which leaves the per-CPU area
+    // in both GS_BASE and KERNEL_GS_BASE. After restoring RSP
     // and popping registers, ret returns to the caller of
     // enter_usermode_returnable.
     //
@@ -436,13 +435,12 @@ pub unsafe fn boot_return_to_kernel() -> ! {
     // is the ONLY way to prevent the optimizer from reusing RAX.
     asm!(
         "mov cr3, rdx",       // Restore boot page tables (CR3 in RDX)
-        "swapgs",              // Balance syscall_entry's swapgs (before touching GS!)
+        "swapgs",              // Balance syscall_entry's swapgs
         "mov ax, 0x10",       // Kernel data segment (GDT index 2, RPL 0)
         "mov ds, ax",         // Restore kernel DS
         "mov es, ax",         // Restore kernel ES
-        "xor eax, eax",       // Zero FS and GS (clobbers RAX but NOT RCX/RDX!)
-        "mov fs, ax",
-        "mov gs, ax",
+        "xor eax, eax",       // Zero FS (clobbers RAX but NOT RCX/RDX!)
+        "mov fs, ax",         // GS keeps the per-CPU base
         "mov rsp, rcx",       // Restore saved boot RSP (RSP in RCX, safe!)
         "add rsp, 8",         // Skip alignment padding from enter_usermode_returnable
         "pop r15",
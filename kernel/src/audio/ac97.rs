@@ -0,0 +1,408 @@
+//! Intel AC'97 Audio Controller Driver
+//!
+//! Driver for the AC'97 controllers of Intel ICH chipsets (PCI 8086:2415
+//! and its successors), as emulated by QEMU (`-device AC97`), VirtualBox and
+//! Bochs. Plays the pipeline output at the codec's fixed 48 kHz, S16 stereo.
+//!
+//! ## Registers
+//!
+//! - **NAM** (BAR0, I/O): the codec mixer -- reset, master and PCM volume
+//! - **NABM** (BAR1, I/O): the bus master -- one DMA engine per direction, each
+//!   fed from a Buffer Descriptor List (BDL) of 32 entries
+//!
+//! ## Playback
+//!
+//! The BDL's entries point at [`NUM_BUFFERS`] period buffers in turn
+//! (entry `i` uses buffer `i % NUM_BUFFERS`). Each period written fills the
+//! next entry and moves the Last Valid Index (LVI) onto it. The engine plays
+//! entries up to the LVI, then halts (DCH) until the next write moves the
+//! LVI again, which resumes it. Entries between the Current Index (CIV) and
+//! the LVI are in flight; with fewer than [`NUM_BUFFERS`] of them the buffer
+//! for the next entry is free. No interrupts are used: the pipeline checks
+//! for room from the timer tick.
+
+#![allow(dead_code)]
+
+use crate::{
+    audio::{pipeline::OutputSink, AudioConfig, AudioError},
+    drivers::pci::PciDevice,
+    error::KernelError,
+    mm::{FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
+};
+
+// ============================================================================
+// Register Definitions
+// ============================================================================
+
+/// Intel vendor ID
+const AC97_VENDOR_ID: u16 = 0x8086;
+
+/// AC'97 audio controllers of ICH, ICH0, ICH2, ICH3, ICH4, ICH5, ICH6 and
+/// ICH7
+const AC97_DEVICE_IDS: [u16; 8] = [
+    0x2415, 0x2425, 0x2445, 0x2485, 0x24C5, 0x24D5, 0x266E, 0x27DE,
+];
+
+/// Native Audio Mixer registers (offsets from BAR0)
+mod nam {
+    /// Writing any value resets the codec
+    pub const RESET: u16 = 0x00;
+    /// Master volume (bit 15: mute, 0: loudest)
+    pub const MASTER_VOLUME: u16 = 0x02;
+    /// PCM out volume (0x0808: 0 dB)
+    pub const PCM_OUT_VOLUME: u16 = 0x18;
+    /// PCM front DAC sample rate
+    pub const PCM_FRONT_RATE: u16 = 0x2C;
+}
+
+/// Native Audio Bus Master registers (offsets from BAR1)
+mod nabm {
+    /// PCM out DMA engine
+    pub const PO_BASE: u16 = 0x10;
+    /// Global control (bit 1: cold reset deasserted)
+    pub const GLOB_CNT: u16 = 0x2C;
+    /// Global status (bit 8: primary codec ready)
+    pub const GLOB_STA: u16 = 0x30;
+
+    // Per-engine registers, relative to the engine base
+    /// Buffer Descriptor List base address (u32)
+    pub const BDBAR: u16 = 0x00;
+    /// Current Index Value (u8)
+    pub const CIV: u16 = 0x04;
+    /// Last Valid Index (u8)
+    pub const LVI: u16 = 0x05;
+    /// Status (u16)
+    pub const SR: u16 = 0x06;
+    /// Control (u8)
+    pub const CR: u16 = 0x0B;
+
+    /// SR: DMA controller halted
+    pub const SR_DCH: u16 = 1 << 0;
+    /// SR: write-1-to-clear status bits (LVBCI, BCIS, FIFOE)
+    pub const SR_W1C: u16 = 0x1C;
+
+    /// CR: run/pause bus master
+    pub const CR_RPBM: u8 = 1 << 0;
+    /// CR: reset the engine's registers
+    pub const CR_RR: u8 = 1 << 1;
+
+    /// GLOB_CNT: cold reset deasserted
+    pub const GLOB_CNT_COLD_RESET: u32 = 1 << 1;
+    /// GLOB_STA: primary codec ready
+    pub const GLOB_STA_PCR: u32 = 1 << 8;
+}
+
+/// Entries in a Buffer Descriptor List
+const BDL_ENTRIES: usize = 32;
+
+/// BDL entry flag: play silence when the engine runs out of buffers
+const BDL_FLAG_BUP: u16 = 1 << 14;
+
+/// Bytes in one period buffer: a pipeline period of 48 kHz S16 stereo
+const PERIOD_BYTES: usize = 4096;
+
+/// Period buffers cycled through by the BDL entries
+const NUM_BUFFERS: usize = 4;
+
+/// DMA frames: one for the BDL, then one per period buffer
+const DMA_FRAMES: usize = 1 + NUM_BUFFERS;
+
+/// Iterations to wait for the codec or an engine reset
+const MAX_SPINS: u32 = 1_000_000;
+
+/// One Buffer Descriptor List entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct BdlEntry {
+    /// Physical address of the buffer
+    addr: u32,
+    /// Number of 16-bit samples in the buffer
+    samples: u16,
+    /// BDL_FLAG_* bits
+    flags: u16,
+}
+
+/// Number of BDL entries queued but not yet played, given the next entry to
+/// fill, the engine's current index and whether it has halted.
+fn in_flight(next: usize, civ: usize, halted: bool) -> usize {
+    if halted {
+        0
+    } else {
+        (next + BDL_ENTRIES - civ) % BDL_ENTRIES
+    }
+}
+
+// ============================================================================
+// AC'97 Controller
+// ============================================================================
+
+/// AC'97 controller playing the pipeline output
+pub struct Ac97Device {
+    /// Native Audio Mixer I/O base
+    nam: u16,
+    /// Native Audio Bus Master I/O base
+    nabm: u16,
+    /// First of the `DMA_FRAMES` contiguous DMA frames
+    frames: FrameNumber,
+    /// Physical address of the DMA frames
+    dma_phys: u64,
+    /// Kernel virtual address of the DMA frames
+    dma_virt: usize,
+    /// Next BDL entry to fill
+    next: usize,
+    /// Whether the PCM out engine has been started
+    running: bool,
+}
+
+impl Ac97Device {
+    /// Reset the controller and codec at the given I/O bases and set up the
+    /// PCM out engine.
+    pub fn new(nam: u16, nabm: u16) -> Result<Self, KernelError> {
+        let frames = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(DMA_FRAMES, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: DMA_FRAMES * FRAME_SIZE,
+                available: 0,
+            })?;
+        let dma_phys = frames.as_u64() * FRAME_SIZE as u64;
+        let device = Self {
+            nam,
+            nabm,
+            frames,
+            dma_phys,
+            dma_virt: crate::mm::phys_to_virt_addr(dma_phys) as usize,
+            next: 0,
+            running: false,
+        };
+        // The bus master takes 32-bit addresses only
+        if dma_phys + (DMA_FRAMES * FRAME_SIZE) as u64 > u32::MAX as u64 {
+            return Err(KernelError::HardwareError {
+                device: "ac97",
+                code: 0x05,
+            });
+        }
+
+        // SAFETY: nam and nabm are the I/O BARs of an AC'97 controller; the
+        // registers written below are defined by the AC'97/ICH specs.
+        unsafe {
+            crate::arch::outl(nabm + nabm::GLOB_CNT, nabm::GLOB_CNT_COLD_RESET);
+            let mut spins = 0;
+            while crate::arch::inl(nabm + nabm::GLOB_STA) & nabm::GLOB_STA_PCR == 0 {
+                spins += 1;
+                if spins >= MAX_SPINS {
+                    return Err(KernelError::Timeout {
+                        operation: "ac97 codec ready",
+                        duration_ms: 0,
+                    });
+                }
+                core::hint::spin_loop();
+            }
+
+            crate::arch::outw(nam + nam::RESET, 0);
+            crate::arch::outw(nam + nam::MASTER_VOLUME, 0);
+            crate::arch::outw(nam + nam::PCM_OUT_VOLUME, 0x0808);
+        }
+        device.reset_engine()?;
+        device.init_bdl();
+        // SAFETY: See above; the BDL lies below 4 GiB (checked above).
+        unsafe {
+            crate::arch::outl(nabm + nabm::PO_BASE + nabm::BDBAR, device.dma_phys as u32);
+        }
+        Ok(device)
+    }
+
+    /// Reset the PCM out engine, waiting for the reset to finish.
+    fn reset_engine(&self) -> Result<(), KernelError> {
+        let base = self.nabm + nabm::PO_BASE;
+        // SAFETY: PCM out engine registers of our controller.
+        unsafe {
+            crate::arch::outb(base + nabm::CR, nabm::CR_RR);
+            let mut spins = 0;
+            while crate::arch::inb(base + nabm::CR) & nabm::CR_RR != 0 {
+                spins += 1;
+                if spins >= MAX_SPINS {
+                    return Err(KernelError::Timeout {
+                        operation: "ac97 engine reset",
+                        duration_ms: 0,
+                    });
+                }
+                core::hint::spin_loop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Point every BDL entry at its period buffer.
+    fn init_bdl(&self) {
+        let bdl = self.dma_virt as *mut BdlEntry;
+        for i in 0..BDL_ENTRIES {
+            let buffer = self.dma_phys + ((1 + i % NUM_BUFFERS) * FRAME_SIZE) as u64;
+            // SAFETY: The BDL's 32 entries fill 256 bytes of our first DMA
+            // frame.
+            unsafe {
+                core::ptr::write_volatile(
+                    bdl.add(i),
+                    BdlEntry {
+                        addr: buffer as u32,
+                        samples: 0,
+                        flags: BDL_FLAG_BUP,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Read the PCM out engine's current index and whether it has halted.
+    fn engine_position(&self) -> (usize, bool) {
+        let base = self.nabm + nabm::PO_BASE;
+        // SAFETY: PCM out engine registers of our controller.
+        unsafe {
+            let civ = crate::arch::inb(base + nabm::CIV) as usize % BDL_ENTRIES;
+            let halted = crate::arch::inw(base + nabm::SR) & nabm::SR_DCH != 0;
+            (civ, halted)
+        }
+    }
+}
+
+impl OutputSink for Ac97Device {
+    fn name(&self) -> &'static str {
+        "AC'97"
+    }
+
+    fn writable_frames(&mut self) -> usize {
+        let queued = if self.running {
+            let (civ, halted) = self.engine_position();
+            in_flight(self.next, civ, halted)
+        } else {
+            0
+        };
+        NUM_BUFFERS.saturating_sub(queued) * PERIOD_BYTES
+            / AudioConfig::default_config().frame_size() as usize
+    }
+
+    fn write(&mut self, samples: &[i16]) -> Result<usize, AudioError> {
+        let samples = &samples[..samples.len().min(PERIOD_BYTES / 2)];
+        if samples.is_empty() {
+            return Ok(0);
+        }
+        if self.writable_frames() == 0 {
+            return Err(AudioError::BufferOverrun);
+        }
+
+        let entry = self.next;
+        let buffer = self.dma_virt + (1 + entry % NUM_BUFFERS) * FRAME_SIZE;
+        let base = self.nabm + nabm::PO_BASE;
+        // SAFETY: With fewer than NUM_BUFFERS entries in flight, the engine
+        // is done with this entry's buffer. The BDL entry and the buffer lie
+        // within our DMA frames; the registers are our controller's.
+        unsafe {
+            core::ptr::copy_nonoverlapping(samples.as_ptr(), buffer as *mut i16, samples.len());
+            let bdl = self.dma_virt as *mut BdlEntry;
+            core::ptr::write_volatile(
+                core::ptr::addr_of_mut!((*bdl.add(entry)).samples),
+                samples.len() as u16,
+            );
+            core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+
+            crate::arch::outw(base + nabm::SR, nabm::SR_W1C);
+            // Moving the LVI resumes a halted engine
+            crate::arch::outb(base + nabm::LVI, entry as u8);
+            if !self.running {
+                crate::arch::outb(base + nabm::CR, nabm::CR_RPBM);
+                self.running = true;
+            }
+        }
+        self.next = (entry + 1) % BDL_ENTRIES;
+
+        Ok(samples.len() / AudioConfig::default_config().channels as usize)
+    }
+}
+
+impl Drop for Ac97Device {
+    fn drop(&mut self) {
+        // Stop the engine before freeing its buffers
+        let _ = self.reset_engine();
+        let _ = FRAME_ALLOCATOR.lock().free_frames(self.frames, DMA_FRAMES);
+    }
+}
+
+// ============================================================================
+// Probing
+// ============================================================================
+
+/// Whether a PCI device is an AC'97 controller.
+fn is_ac97(device: &PciDevice) -> bool {
+    device.vendor_id == AC97_VENDOR_ID && AC97_DEVICE_IDS.contains(&device.device_id)
+}
+
+/// Find and initialize an AC'97 controller on the PCI bus
+pub fn probe() -> Option<Ac97Device> {
+    use crate::drivers::pci;
+
+    if !pci::is_pci_initialized() {
+        return None;
+    }
+    let devices = pci::get_pci_bus().lock().get_all_devices();
+    for device in devices.iter().filter(|device| is_ac97(device)) {
+        let io = |bar: usize| device.bars.get(bar).and_then(|bar| bar.get_io_address());
+        let (Some(nam), Some(nabm)) = (io(0), io(1)) else {
+            continue;
+        };
+        pci::enable_bus_master(device.location);
+
+        match Ac97Device::new(nam as u16, nabm as u16) {
+            Ok(ac97) => {
+                println!(
+                    "[AUDIO] AC'97: controller at {:02x}:{:02x}.{} (NAM {:#x}, NABM {:#x})",
+                    device.location.bus,
+                    device.location.device,
+                    device.location.function,
+                    nam,
+                    nabm
+                );
+                return Some(ac97);
+            }
+            Err(_e) => {
+                println!("[AUDIO] AC'97: init failed: {:?}", _e);
+            }
+        }
+    }
+    None
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bdl_entry_size() {
+        assert_eq!(core::mem::size_of::<BdlEntry>(), 8);
+        assert!(BDL_ENTRIES * core::mem::size_of::<BdlEntry>() <= FRAME_SIZE);
+    }
+
+    #[test]
+    fn test_in_flight() {
+        assert_eq!(in_flight(0, 0, false), 0);
+        assert_eq!(in_flight(3, 1, false), 2);
+        // Wrapped around the end of the BDL
+        assert_eq!(in_flight(1, 30, false), 3);
+        // A halted engine has played everything
+        assert_eq!(in_flight(5, 4, true), 0);
+    }
+
+    #[test]
+    fn test_period_matches_pipeline() {
+        let config = AudioConfig::default_config();
+        assert_eq!(
+            PERIOD_BYTES,
+            config.buffer_frames as usize * config.frame_size() as usize
+        );
+        assert!(PERIOD_BYTES <= FRAME_SIZE);
+        assert!(NUM_BUFFERS < BDL_ENTRIES);
+    }
+}
@@ -251,33 +251,57 @@ impl SharedAudioBuffer {
     /// Write i16 samples into the buffer
     ///
     /// Converts the sample slice to bytes and writes into the ring buffer.
-    /// Returns the number of samples actually written.
+    /// Only whole frames are written, so the reader never sees a split
+    /// frame. Returns the number of samples actually written.
     pub fn write_samples(&self, samples: &[i16]) -> usize {
+        let ring = self.inner.lock();
+        let samples = &samples[..self.whole_frames(samples.len(), ring.available_write())];
         // SAFETY: Reinterpreting &[i16] as &[u8]; i16 alignment >= u8, length scaled by
         // 2.
         let bytes: &[u8] = unsafe {
             core::slice::from_raw_parts(samples.as_ptr() as *const u8, samples.len() * 2)
         };
-        let ring = self.inner.lock();
         let bytes_written = ring.write(bytes);
         bytes_written / 2
     }
 
     /// Read i16 samples from the buffer
     ///
-    /// Reads bytes from the ring buffer and reinterprets as i16 samples.
-    /// Returns the number of samples actually read.
+    /// Reads whole frames from the ring buffer and reinterprets them as i16
+    /// samples. Returns the number of samples actually read.
     pub fn read_samples(&self, output: &mut [i16]) -> usize {
+        let ring = self.inner.lock();
+        self.read_from(&ring, output)
+    }
+
+    /// Like [`read_samples`](Self::read_samples), but returns `None` instead
+    /// of waiting when a writer holds the buffer. Used from the timer tick.
+    pub fn try_read_samples(&self, output: &mut [i16]) -> Option<usize> {
+        let ring = self.inner.try_lock()?;
+        Some(self.read_from(&ring, output))
+    }
+
+    fn read_from(&self, ring: &AudioRingBuffer, output: &mut [i16]) -> usize {
+        let len = self.whole_frames(output.len(), ring.available_read());
         // SAFETY: Reinterpreting &mut [i16] as &mut [u8]; i16 alignment >= u8, length
         // scaled by 2.
-        let bytes: &mut [u8] = unsafe {
-            core::slice::from_raw_parts_mut(output.as_mut_ptr() as *mut u8, output.len() * 2)
-        };
-        let ring = self.inner.lock();
+        let bytes: &mut [u8] =
+            unsafe { core::slice::from_raw_parts_mut(output.as_mut_ptr() as *mut u8, len * 2) };
         let bytes_read = ring.read(bytes);
         bytes_read / 2
     }
 
+    /// Samples in at most `frames` whole frames that fit into `samples`.
+    fn whole_frames(&self, samples: usize, frames: u32) -> usize {
+        let channels = self.config.channels.max(1) as usize;
+        (samples / channels).min(frames as usize) * channels
+    }
+
+    /// Discard all buffered samples
+    pub fn clear(&self) {
+        self.inner.lock().clear();
+    }
+
     /// Number of frames available to read
     pub fn available_read_frames(&self) -> u32 {
         self.inner.lock().available_read()
@@ -441,4 +465,24 @@ mod tests {
         assert!(buf.is_empty());
         assert_eq!(buf.available_read_frames(), 0);
     }
+
+    #[test]
+    fn test_shared_buffer_whole_frames() {
+        let config = AudioConfig {
+            channels: 2,
+            ..test_config()
+        };
+        // 4 frames of 4 bytes: 15 usable bytes, so only 3 whole frames fit
+        let buf = SharedAudioBuffer::new(4, config);
+        assert_eq!(buf.write_samples(&[1i16; 10]), 6);
+        // An odd sample count is cut to whole frames too
+        assert_eq!(buf.write_samples(&[1i16; 1]), 0);
+
+        let mut output = [0i16; 3];
+        assert_eq!(buf.read_samples(&mut output), 2);
+        assert_eq!(buf.try_read_samples(&mut output), Some(2));
+
+        buf.clear();
+        assert!(buf.is_empty());
+    }
 }
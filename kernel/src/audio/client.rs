@@ -2,7 +2,9 @@
 //!
 //! Provides the user-facing interface for creating, controlling, and writing
 //! to audio streams. Each stream is backed by a `SharedAudioBuffer` and a
-//! corresponding mixer channel.
+//! corresponding mixer channel. Clients write into the buffer; the output
+//! pipeline moves one period of every playing stream into its mixer channel
+//! per mix cycle (see [`AudioClient::fill_mixer`]).
 
 #![allow(dead_code)]
 
@@ -10,7 +12,7 @@ use alloc::{collections::BTreeMap, string::String};
 use core::sync::atomic::{AtomicU32, Ordering};
 
 use crate::{
-    audio::{buffer::SharedAudioBuffer, AudioConfig},
+    audio::{buffer::SharedAudioBuffer, mixer::AudioMixer, AudioConfig, SampleFormat},
     error::KernelError,
 };
 
@@ -83,12 +85,33 @@ impl AudioClient {
     /// Create a new audio stream
     ///
     /// Allocates a ring buffer and registers a mixer channel for the stream.
-    /// The stream starts in the `Stopped` state.
+    /// The stream starts in the `Stopped` state. The mixer does not resample
+    /// or convert: streams must be S16 mono or stereo at the output rate.
     pub fn create_stream(
         &mut self,
         name: &str,
         config: AudioConfig,
     ) -> Result<AudioStreamId, KernelError> {
+        let output = AudioConfig::default_config();
+        if config.sample_rate != output.sample_rate {
+            return Err(KernelError::InvalidArgument {
+                name: "sample_rate",
+                value: "must match the 48000 Hz output rate",
+            });
+        }
+        if !matches!(config.channels, 1 | 2) {
+            return Err(KernelError::InvalidArgument {
+                name: "channels",
+                value: "must be 1 or 2",
+            });
+        }
+        if config.format != SampleFormat::S16Le {
+            return Err(KernelError::InvalidArgument {
+                name: "format",
+                value: "must be S16Le",
+            });
+        }
+
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
         let stream_id = AudioStreamId(id);
 
@@ -100,8 +123,16 @@ impl AudioClient {
         };
         let buffer = SharedAudioBuffer::new(buffer_frames, config);
 
-        // Register a mixer channel for this stream
-        let mixer_channel_id = crate::audio::mixer::with_mixer(|mixer| mixer.add_channel(name))?;
+        // Register a mixer channel for this stream, with room for one output
+        // period so that filling it from the timer tick never allocates
+        let period_samples = output.buffer_frames as usize * output.channels as usize;
+        let mixer_channel_id = crate::audio::mixer::with_mixer(|mixer| {
+            let id = mixer.add_channel(name);
+            if let Some(channel) = mixer.get_channel_mut(id) {
+                channel.reserve(period_samples);
+            }
+            id
+        })?;
 
         let stream = AudioStream {
             id: stream_id,
@@ -137,7 +168,7 @@ impl AudioClient {
     /// Write i16 samples to a stream's buffer
     ///
     /// Returns the number of samples actually written (may be less if the
-    /// buffer is full).
+    /// buffer is full). Only whole frames are written.
     pub fn write_samples(
         &mut self,
         id: AudioStreamId,
@@ -148,15 +179,7 @@ impl AudioClient {
             id: id.0 as u64,
         })?;
 
-        let written = stream.buffer.write_samples(samples);
-
-        // Also feed samples to the mixer channel so they appear in mixed output
-        let channel_id = stream.mixer_channel_id;
-        let _ = crate::audio::mixer::with_mixer(|mixer| {
-            mixer.write_channel_samples(channel_id, samples);
-        });
-
-        Ok(written)
+        Ok(stream.buffer.write_samples(samples))
     }
 
     /// Start playing a stream
@@ -190,7 +213,7 @@ impl AudioClient {
         Ok(())
     }
 
-    /// Stop a stream (resets to beginning)
+    /// Stop a stream, discarding any samples not yet played
     pub fn stop(&mut self, id: AudioStreamId) -> Result<(), KernelError> {
         let stream = self.streams.get_mut(&id.0).ok_or(KernelError::NotFound {
             resource: "audio stream",
@@ -198,6 +221,7 @@ impl AudioClient {
         })?;
 
         stream.state = StreamState::Stopped;
+        stream.buffer.clear();
         println!("[AUDIO] Stream '{}' (id={}) -> Stopped", stream.name, id.0);
         Ok(())
     }
@@ -226,16 +250,71 @@ impl AudioClient {
         Ok(stream.state)
     }
 
-    /// Close and remove an audio stream
+    /// Close and remove an audio stream and its mixer channel
     pub fn close_stream(&mut self, id: AudioStreamId) -> Result<(), KernelError> {
-        self.streams.remove(&id.0).ok_or(KernelError::NotFound {
+        let stream = self.streams.remove(&id.0).ok_or(KernelError::NotFound {
             resource: "audio stream",
             id: id.0 as u64,
         })?;
+        let _ = crate::audio::mixer::with_mixer(|mixer| {
+            mixer.remove_channel(stream.mixer_channel_id);
+        });
         println!("[AUDIO] Closed stream {}", id.0);
         Ok(())
     }
 
+    /// Frames buffered in a stream and not yet mixed
+    pub fn queued_frames(&self, id: AudioStreamId) -> Result<u32, KernelError> {
+        let stream = self.streams.get(&id.0).ok_or(KernelError::NotFound {
+            resource: "audio stream",
+            id: id.0 as u64,
+        })?;
+        Ok(stream.buffer.available_read_frames())
+    }
+
+    /// Move up to `frames` frames of every playing stream into its mixer
+    /// channel, upmixing mono streams to the mixer's `out_channels`
+    ///
+    /// Channels of streams that are not playing are emptied. Returns whether
+    /// any stream delivered samples. Called by the output pipeline, also from
+    /// the timer tick: does not allocate and skips a stream rather than wait
+    /// for its buffer lock.
+    pub(crate) fn fill_mixer(
+        &self,
+        mixer: &mut AudioMixer,
+        frames: usize,
+        out_channels: usize,
+    ) -> bool {
+        let mut any = false;
+        for stream in self.streams.values() {
+            let Some(channel) = mixer.get_channel_mut(stream.mixer_channel_id) else {
+                continue;
+            };
+            let in_channels = stream.config.channels as usize;
+            if stream.state != StreamState::Playing
+                || !(in_channels == out_channels || in_channels == 1)
+            {
+                channel.refill(0, |_| 0);
+                continue;
+            }
+            let mut delivered = false;
+            channel.refill(frames * out_channels, |buf| {
+                let read = stream
+                    .buffer
+                    .try_read_samples(&mut buf[..frames * in_channels])
+                    .unwrap_or(0);
+                let read_frames = read / in_channels;
+                if in_channels != out_channels {
+                    upmix_in_place(buf, read_frames, out_channels);
+                }
+                delivered = read_frames > 0;
+                read_frames * out_channels
+            });
+            any |= delivered;
+        }
+        any
+    }
+
     /// Get the number of active streams
     pub fn stream_count(&self) -> usize {
         self.streams.len()
@@ -252,6 +331,16 @@ impl AudioClient {
     }
 }
 
+/// Spread `frames` mono samples at the start of `buf` over `channels`
+/// interleaved channels. Works backwards so no sample is overwritten before
+/// it is copied.
+fn upmix_in_place(buf: &mut [i16], frames: usize, channels: usize) {
+    for frame in (0..frames).rev() {
+        let sample = buf[frame];
+        buf[frame * channels..(frame + 1) * channels].fill(sample);
+    }
+}
+
 // ============================================================================
 // Global Client State
 // ============================================================================
@@ -265,6 +354,12 @@ pub fn init() {
     println!("[AUDIO] Client manager initialized");
 }
 
+/// Like [`with_client`], but returns `None` instead of waiting when the
+/// client manager is busy. Used from the timer tick.
+pub(crate) fn try_with_client<R, F: FnOnce(&mut AudioClient) -> R>(f: F) -> Option<R> {
+    CLIENT.try_lock()?.as_mut().map(f)
+}
+
 /// Access the global audio client through a closure
 pub fn with_client<R, F: FnOnce(&mut AudioClient) -> R>(f: F) -> Result<R, KernelError> {
     let mut guard = CLIENT.lock();
@@ -305,4 +400,27 @@ mod tests {
         assert_eq!(StreamState::Stopped, StreamState::Stopped);
         assert_ne!(StreamState::Playing, StreamState::Paused);
     }
+
+    #[test]
+    fn test_upmix_in_place() {
+        let mut buf = [1i16, 2, 3, 0, 0, 0];
+        upmix_in_place(&mut buf, 3, 2);
+        assert_eq!(buf, [1, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn test_create_stream_rejects_other_rates() {
+        let mut client = AudioClient::new();
+        let config = AudioConfig {
+            sample_rate: 44100,
+            ..test_config()
+        };
+        assert!(client.create_stream("test", config).is_err());
+        let config = AudioConfig {
+            channels: 6,
+            ..test_config()
+        };
+        assert!(client.create_stream("test", config).is_err());
+        assert_eq!(client.stream_count(), 0);
+    }
 }
@@ -159,6 +159,25 @@ impl MixerChannel {
         self.buffer_index = 0;
     }
 
+    /// Refill this channel's buffer in place for the next mix cycle
+    ///
+    /// `fill` gets `len` samples to write into and returns how many it
+    /// wrote. Does not allocate once [`reserve`](Self::reserve) made room
+    /// for `len` samples, so the output pipeline can call it from the timer
+    /// tick.
+    pub fn refill<F: FnOnce(&mut [i16]) -> usize>(&mut self, len: usize, fill: F) {
+        self.samples.resize(len, 0);
+        let written = fill(&mut self.samples);
+        self.samples.truncate(written);
+        self.buffer_index = 0;
+    }
+
+    /// Make room for `samples` samples per mix cycle
+    pub fn reserve(&mut self, samples: usize) {
+        self.samples
+            .reserve(samples.saturating_sub(self.samples.len()));
+    }
+
     /// Read and consume up to `count` samples from this channel
     fn read_samples(&mut self, count: usize) -> &[i16] {
         let start = self.buffer_index as usize;
@@ -342,6 +361,12 @@ pub fn init(sample_rate: u32) -> Result<(), KernelError> {
     Ok(())
 }
 
+/// Like [`with_mixer`], but returns `None` instead of waiting when the
+/// mixer is busy. Used from the timer tick.
+pub(crate) fn try_with_mixer<R, F: FnOnce(&mut AudioMixer) -> R>(f: F) -> Option<R> {
+    MIXER.try_lock()?.as_mut().map(f)
+}
+
 /// Access the global mixer through a closure
 pub fn with_mixer<R, F: FnOnce(&mut AudioMixer) -> R>(f: F) -> Result<R, KernelError> {
     let mut guard = MIXER.lock();
@@ -515,4 +540,27 @@ mod tests {
             assert!(sample > 0, "Saturation failed: got {}", sample);
         }
     }
+
+    #[test]
+    fn test_channel_refill() {
+        let mut mixer = AudioMixer::new(48000, 1);
+        let ch_id = mixer.add_channel("test");
+        let channel = mixer.get_channel_mut(ch_id).unwrap();
+        channel.reserve(4);
+
+        // Short fill: the tail of the period stays silent
+        channel.refill(4, |buf| {
+            buf[..2].copy_from_slice(&[1000, 2000]);
+            2
+        });
+        let mut output = [0i16; 4];
+        mixer.mix_to_output(&mut output);
+        assert!(output[0] > 0 && output[1] > 0);
+        assert_eq!(&output[2..], &[0, 0]);
+
+        // An empty refill silences the channel
+        mixer.get_channel_mut(ch_id).unwrap().refill(0, |_| 0);
+        mixer.mix_to_output(&mut output);
+        assert_eq!(output, [0; 4]);
+    }
 }
@@ -5,11 +5,17 @@
 //! - Ring buffer transport for audio streams
 //! - WAV file parsing (PCM formats)
 //! - Client API for creating and managing audio streams
-//! - Output pipeline with underrun detection
-//! - VirtIO-Sound driver for paravirtualized audio
+//! - Output pipeline feeding the output device from the timer tick
+//! - VirtIO-Sound driver for paravirtualized audio (AArch64, RISC-V)
+//! - AC'97 driver for emulated and ICH-era hardware (x86_64)
+//!
+//! User programs open streams through the audio syscalls (`SYS_AUDIO_*`),
+//! wrapped by `<veridian/audio.h>` in libc.
 
 #![allow(dead_code)]
 
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod ac97;
 pub mod alsa;
 pub mod buffer;
 pub mod client;
@@ -235,8 +241,8 @@ pub struct AudioDeviceInfo {
 
 /// Initialize the audio subsystem
 ///
-/// Sets up the mixer, output pipeline, client manager, and probes for
-/// VirtIO-Sound hardware.
+/// Sets up the mixer, output pipeline, client manager, and probes for an
+/// output device: AC'97 on x86_64, VirtIO-Sound elsewhere.
 pub fn init() -> Result<(), KernelError> {
     println!("[AUDIO] Initializing audio subsystem...");
 
@@ -249,10 +255,16 @@ pub fn init() -> Result<(), KernelError> {
     // Initialize the client manager
     client::init();
 
-    // Probe for VirtIO-Sound hardware (non-fatal if absent)
-    if let Err(_e) = virtio_sound::init() {
-        println!("[AUDIO] No VirtIO-Sound device found (non-fatal)");
+    // Probe for output hardware (non-fatal if absent)
+    #[cfg(target_arch = "x86_64")]
+    let output = ac97::probe().map(|dev| alloc::boxed::Box::new(dev) as _);
+    #[cfg(not(target_arch = "x86_64"))]
+    let output = virtio_sound::probe().map(|dev| alloc::boxed::Box::new(dev) as _);
+    match output {
+        Some(sink) => pipeline::set_output(sink)?,
+        None => println!("[AUDIO] No output device found (non-fatal)"),
     }
+    pipeline::with_pipeline(|pipeline| pipeline.start())?;
 
     println!("[AUDIO] Audio subsystem initialized");
     Ok(())
@@ -3,13 +3,42 @@
 //! Manages the flow of mixed audio data to the output device. The pipeline
 //! periodically calls the mixer to produce output frames and tracks
 //! statistics such as total frames processed and buffer underrun events.
+//!
+//! The output device is an [`OutputSink`] registered by its driver through
+//! [`set_output`]. On every timer tick (and right after a client writes),
+//! [`pump`] mixes one period of the playing streams for as long as the device
+//! has room for it, so the device queue stays a few periods ahead of the
+//! hardware.
 
 #![allow(dead_code)]
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 use core::sync::atomic::{AtomicU64, Ordering};
 
-use crate::{audio::AudioConfig, error::KernelError};
+use crate::{
+    audio::{AudioConfig, AudioError},
+    error::KernelError,
+};
+
+// ============================================================================
+// Output Sink
+// ============================================================================
+
+/// An audio device playing the pipeline's mixed output
+///
+/// Samples are interleaved S16 in the pipeline's output configuration
+/// (48 kHz stereo). Both methods are called from the timer tick with the
+/// pipeline lock held: they must not block or allocate.
+pub trait OutputSink: Send {
+    /// Human-readable device name
+    fn name(&self) -> &'static str;
+
+    /// Number of frames the device can queue right now
+    fn writable_frames(&mut self) -> usize;
+
+    /// Queue interleaved samples for playback, returning the frames queued
+    fn write(&mut self, samples: &[i16]) -> Result<usize, AudioError>;
+}
 
 // ============================================================================
 // Pipeline State
@@ -56,6 +85,8 @@ pub struct AudioPipeline {
     frames_processed: AtomicU64,
     /// Total underrun events
     underruns: AtomicU64,
+    /// Device playing the mixed output, if any
+    output: Option<Box<dyn OutputSink>>,
 }
 
 impl AudioPipeline {
@@ -68,7 +99,60 @@ impl AudioPipeline {
             output_config: config,
             frames_processed: AtomicU64::new(0),
             underruns: AtomicU64::new(0),
+            output: None,
+        }
+    }
+
+    /// Send the mixed output to `sink`, replacing any previous device
+    pub fn set_output(&mut self, sink: Box<dyn OutputSink>) {
+        println!("[AUDIO] Output device: {}", sink.name());
+        self.output = Some(sink);
+    }
+
+    /// Whether an output device is attached
+    pub fn has_output(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Mix and queue periods while the output device has room for them
+    ///
+    /// Stops early when no stream has samples to play, or when the client
+    /// manager or mixer is busy, rather than waiting. Returns the number of
+    /// periods queued.
+    pub fn pump(&mut self) -> usize {
+        if self.state != PipelineState::Running {
+            return 0;
+        }
+        let Some(output) = self.output.as_mut() else {
+            return 0;
+        };
+        let frames = self.output_config.buffer_frames as usize;
+        let channels = self.output_config.channels as usize;
+        let output_buffer = &mut self.output_buffer;
+
+        let mut periods = 0;
+        while output.writable_frames() >= frames {
+            let mixed = crate::audio::client::try_with_client(|client| {
+                crate::audio::mixer::try_with_mixer(|mixer| {
+                    let any = client.fill_mixer(mixer, frames, channels);
+                    if any {
+                        mixer.mix_to_output(output_buffer);
+                    }
+                    any
+                })
+            });
+            if mixed != Some(Some(true)) {
+                break;
+            }
+            if output.write(output_buffer).is_err() {
+                self.underruns.fetch_add(1, Ordering::Relaxed);
+                break;
+            }
+            self.frames_processed
+                .fetch_add(frames as u64, Ordering::Relaxed);
+            periods += 1;
         }
+        periods
     }
 
     /// Process one frame period: mix all active channels into the output buffer
@@ -97,13 +181,6 @@ impl AudioPipeline {
                     self.underruns.fetch_add(1, Ordering::Relaxed);
                 }
 
-                // Submit mixed audio to VirtIO-Sound hardware (if present)
-                if !all_silence {
-                    let _ = crate::audio::virtio_sound::with_device(|dev| {
-                        let _ = dev.write_pcm(0, &self.output_buffer);
-                    });
-                }
-
                 let frames = self.output_config.buffer_frames as u64;
                 self.frames_processed.fetch_add(frames, Ordering::Relaxed);
             }
@@ -207,6 +284,31 @@ pub fn init(config: AudioConfig) -> Result<(), KernelError> {
     Ok(())
 }
 
+/// Send the mixed output to `sink`, replacing any previous device
+pub fn set_output(sink: Box<dyn OutputSink>) -> Result<(), KernelError> {
+    with_pipeline(|pipeline| pipeline.set_output(sink))
+}
+
+/// Whether an output device is attached
+pub fn has_output() -> bool {
+    PIPELINE
+        .lock()
+        .as_ref()
+        .is_some_and(AudioPipeline::has_output)
+}
+
+/// Queue as much mixed output as the device takes
+///
+/// Called on every timer tick and after clients write samples; skips the
+/// round rather than wait when the pipeline is busy.
+pub fn pump() {
+    if let Some(mut guard) = PIPELINE.try_lock() {
+        if let Some(pipeline) = guard.as_mut() {
+            pipeline.pump();
+        }
+    }
+}
+
 /// Access the global pipeline through a closure
 pub fn with_pipeline<R, F: FnOnce(&mut AudioPipeline) -> R>(f: F) -> Result<R, KernelError> {
     let mut guard = PIPELINE.lock();
@@ -270,6 +372,49 @@ mod tests {
         assert_eq!(stats.state, PipelineState::Idle);
     }
 
+    /// Sink accepting a fixed number of frames
+    struct TestSink {
+        room: usize,
+        written: usize,
+    }
+
+    impl OutputSink for TestSink {
+        fn name(&self) -> &'static str {
+            "test"
+        }
+
+        fn writable_frames(&mut self) -> usize {
+            self.room
+        }
+
+        fn write(&mut self, samples: &[i16]) -> Result<usize, AudioError> {
+            let frames = samples.len() / 2;
+            self.room -= frames;
+            self.written += frames;
+            Ok(frames)
+        }
+    }
+
+    #[test]
+    fn test_pipeline_pump_without_output() {
+        let mut pipeline = AudioPipeline::new(test_config());
+        pipeline.start();
+        assert!(!pipeline.has_output());
+        assert_eq!(pipeline.pump(), 0);
+    }
+
+    #[test]
+    fn test_pipeline_pump_idle() {
+        let mut pipeline = AudioPipeline::new(test_config());
+        pipeline.set_output(Box::new(TestSink {
+            room: 1024,
+            written: 0,
+        }));
+        assert!(pipeline.has_output());
+        // Not started: nothing is queued
+        assert_eq!(pipeline.pump(), 0);
+    }
+
     #[test]
     fn test_pipeline_idle_silence() {
         let config = test_config();
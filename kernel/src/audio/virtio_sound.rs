@@ -1,41 +1,64 @@
 //! VirtIO-Sound Driver
 //!
-//! Driver for paravirtualized audio devices using the VirtIO Sound protocol.
-//! Commonly used in QEMU/KVM virtual machines for audio playback and capture.
+//! Driver for paravirtualized audio devices using the VirtIO Sound protocol
+//! (virtio spec 5.14). Commonly used in QEMU/KVM virtual machines for audio
+//! playback and capture.
 //!
 //! ## VirtIO Sound Device
 //!
-//! PCI Device ID: 0x1AF4 (vendor), 0x1059 (device, sound)
+//! Device type 25 behind the virtio-mmio transport, as on the QEMU `virt`
+//! machines for AArch64 and RISC-V (`-device virtio-sound-device`). The PCI
+//! variant (0x1AF4:0x1059) is modern-only and needs the virtio 1.0 PCI
+//! transport, which the kernel does not implement; x86_64 uses AC'97.
 //!
-//! The driver uses four virtqueues:
+//! The device has four virtqueues:
 //! - **controlq** (queue 0): Configuration and control messages
-//! - **eventq** (queue 1): Asynchronous event notifications
+//! - **eventq** (queue 1): Asynchronous event notifications (jack hotplug)
 //! - **txq** (queue 2): PCM output (playback) data
 //! - **rxq** (queue 3): PCM input (capture) data
 //!
+//! Only playback is driven: the event and capture queues stay unused.
+//!
 //! ## Protocol
 //!
-//! Communication uses request/response messages through the control virtqueue.
-//! PCM data flows through tx/rx virtqueues with per-buffer headers.
+//! Control requests are a device-readable request followed by a
+//! device-writable response, and are polled for completion. At probe the
+//! driver picks the first output stream that does S16 stereo at 48 kHz, sets
+//! its parameters to [`NUM_PERIODS`] periods of one pipeline period each,
+//! prepares and starts it. Each period then goes out on the tx queue as a
+//! `VirtioSndPcmXfer` header, the samples, and a device-writable
+//! `VirtioSndPcmStatus`; the device returns the buffer once it has played
+//! it, which frees its slot for the next period.
 
 #![allow(dead_code)]
 
-use alloc::{string::String, vec, vec::Vec};
+use alloc::vec::Vec;
+use core::sync::atomic::{self, Ordering};
 
 use crate::{
-    audio::{AudioConfig, AudioDevice, AudioDeviceCapabilities, AudioError, SampleFormat},
+    audio::{pipeline::OutputSink, AudioConfig, AudioError},
+    drivers::virtio::{
+        mmio::VirtioMmioTransport,
+        queue::{VirtQueue, VIRTQ_DESC_F_NEXT, VIRTQ_DESC_F_WRITE},
+    },
     error::KernelError,
+    mm::{FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
 };
 
 // ============================================================================
 // VirtIO Sound Protocol Constants
 // ============================================================================
 
-/// VirtIO Sound PCI vendor ID
-const VIRTIO_SND_PCI_VENDOR: u16 = 0x1AF4;
+/// Virtio device type for sound devices
+const VIRTIO_ID_SOUND: u32 = 25;
+
+/// VIRTIO_F_VERSION_1 (feature bit 32), in the high feature word
+const VIRTIO_F_VERSION_1_HIGH: u32 = 1 << 0;
 
-/// VirtIO Sound PCI device ID
-const VIRTIO_SND_PCI_DEVICE: u16 = 0x1059;
+/// Control virtqueue index
+const CONTROL_QUEUE: u16 = 0;
+/// Playback virtqueue index
+const TX_QUEUE: u16 = 2;
 
 // --- Control request types ---
 
@@ -71,26 +94,30 @@ const VIRTIO_SND_S_NOT_SUPP: u32 = 0x8002;
 /// I/O error
 const VIRTIO_SND_S_IO_ERR: u32 = 0x8003;
 
-// --- PCM sample formats ---
+// --- PCM sample formats (virtio spec 5.14.6.6.3) ---
 
 /// IMA ADPCM (not used for PCM)
 const VIRTIO_SND_PCM_FMT_IMA_ADPCM: u8 = 0;
 /// Mu-law
 const VIRTIO_SND_PCM_FMT_MU_LAW: u8 = 1;
+/// A-law
+const VIRTIO_SND_PCM_FMT_A_LAW: u8 = 2;
+/// Signed 8-bit
+const VIRTIO_SND_PCM_FMT_S8: u8 = 3;
+/// Unsigned 8-bit
+const VIRTIO_SND_PCM_FMT_U8: u8 = 4;
 /// Signed 16-bit
-const VIRTIO_SND_PCM_FMT_S16: u8 = 2;
-/// Signed 24-bit
-const VIRTIO_SND_PCM_FMT_S24: u8 = 3;
+const VIRTIO_SND_PCM_FMT_S16: u8 = 5;
+/// Signed 24-bit, packed in 3 bytes
+const VIRTIO_SND_PCM_FMT_S24_3: u8 = 11;
+/// Signed 24-bit in 4 bytes
+const VIRTIO_SND_PCM_FMT_S24: u8 = 15;
 /// Signed 32-bit
-const VIRTIO_SND_PCM_FMT_S32: u8 = 4;
+const VIRTIO_SND_PCM_FMT_S32: u8 = 17;
 /// 32-bit float
-const VIRTIO_SND_PCM_FMT_FLOAT: u8 = 5;
+const VIRTIO_SND_PCM_FMT_FLOAT: u8 = 19;
 /// 64-bit float
-const VIRTIO_SND_PCM_FMT_FLOAT64: u8 = 6;
-/// Unsigned 8-bit
-const VIRTIO_SND_PCM_FMT_U8: u8 = 8;
-/// Signed 16-bit big-endian
-const VIRTIO_SND_PCM_FMT_S16_BE: u8 = 18;
+const VIRTIO_SND_PCM_FMT_FLOAT64: u8 = 20;
 
 // --- PCM sample rates ---
 
@@ -107,19 +134,21 @@ const VIRTIO_SND_PCM_RATE_22050: u8 = 4;
 /// 32000 Hz
 const VIRTIO_SND_PCM_RATE_32000: u8 = 5;
 /// 44100 Hz
-const VIRTIO_SND_PCM_RATE_44100: u8 = 8;
+const VIRTIO_SND_PCM_RATE_44100: u8 = 6;
 /// 48000 Hz
-const VIRTIO_SND_PCM_RATE_48000: u8 = 9;
+const VIRTIO_SND_PCM_RATE_48000: u8 = 7;
 /// 64000 Hz
-const VIRTIO_SND_PCM_RATE_64000: u8 = 10;
+const VIRTIO_SND_PCM_RATE_64000: u8 = 8;
 /// 88200 Hz
-const VIRTIO_SND_PCM_RATE_88200: u8 = 11;
+const VIRTIO_SND_PCM_RATE_88200: u8 = 9;
 /// 96000 Hz
-const VIRTIO_SND_PCM_RATE_96000: u8 = 12;
+const VIRTIO_SND_PCM_RATE_96000: u8 = 10;
 /// 176400 Hz
-const VIRTIO_SND_PCM_RATE_176400: u8 = 13;
+const VIRTIO_SND_PCM_RATE_176400: u8 = 11;
 /// 192000 Hz
-const VIRTIO_SND_PCM_RATE_192000: u8 = 14;
+const VIRTIO_SND_PCM_RATE_192000: u8 = 12;
+/// 384000 Hz
+const VIRTIO_SND_PCM_RATE_384000: u8 = 13;
 
 // --- PCM stream directions ---
 
@@ -230,574 +259,396 @@ pub struct VirtioSndPcmInfo {
 // VirtIO Sound Device Driver
 // ============================================================================
 
-/// VirtIO Sound device state
+/// Bytes in one period: a pipeline period of 48 kHz S16 stereo
+const PERIOD_BYTES: usize = 4096;
+
+/// Periods queued on the device at once
+const NUM_PERIODS: usize = 4;
+
+/// Most PCM streams inspected when looking for an output stream
+const MAX_STREAMS: usize = 16;
+
+/// Iterations to wait for a control request before giving up
+const MAX_SPINS: u32 = 10_000_000;
+
+/// DMA frames: one for control requests, one for transfer headers and
+/// statuses, then one per period
+const DMA_FRAMES: usize = 2 + NUM_PERIODS;
+
+/// Offset of the control response within the control frame
+const CONTROL_RESPONSE: usize = 256;
+
+/// Pick the first output stream that plays S16 stereo at 48 kHz
+fn find_output_stream(infos: &[VirtioSndPcmInfo]) -> Option<u32> {
+    infos
+        .iter()
+        .position(|info| {
+            info.direction == VIRTIO_SND_D_OUTPUT
+                && info.formats & (1 << VIRTIO_SND_PCM_FMT_S16) != 0
+                && info.rates & (1 << VIRTIO_SND_PCM_RATE_48000) != 0
+                && info.channels_min <= 2
+                && info.channels_max >= 2
+        })
+        .map(|index| index as u32)
+}
+
+/// VirtIO Sound device playing the pipeline output on one PCM stream
 pub struct VirtioSoundDevice {
-    /// PCI device ID for reference
-    pci_device_id: u32,
-    /// MMIO base address for device registers
-    mmio_base: usize,
-    /// Control virtqueue index
-    control_queue: u16,
-    /// Event virtqueue index
-    event_queue: u16,
-    /// TX (playback) virtqueue index
-    tx_queue: u16,
-    /// RX (capture) virtqueue index
-    rx_queue: u16,
-    /// Number of audio jacks reported by device
-    num_jacks: u32,
-    /// Number of PCM streams reported by device
-    num_streams: u32,
-    /// Number of channel maps reported by device
-    num_chmaps: u32,
-    /// Whether the device has been successfully initialized
-    initialized: bool,
-    /// Pending TX buffer data
-    tx_buffer: Vec<u8>,
-    /// Device name for AudioDevice trait
-    device_name: String,
-    /// Cached device capabilities for AudioDevice trait
-    device_capabilities: AudioDeviceCapabilities,
-    /// Currently active stream ID for AudioDevice trait operations
-    active_stream_id: u32,
-    /// Current audio configuration
-    current_config: Option<AudioConfig>,
-    /// Whether a stream is currently running
-    stream_running: bool,
+    /// MMIO transport
+    transport: VirtioMmioTransport,
+    /// Control virtqueue
+    control: VirtQueue,
+    /// Playback virtqueue
+    tx: VirtQueue,
+    /// First of the `DMA_FRAMES` contiguous DMA frames
+    frames: FrameNumber,
+    /// Physical address of the DMA frames
+    dma_phys: u64,
+    /// Kernel virtual address of the DMA frames
+    dma_virt: usize,
+    /// PCM stream used for playback
+    stream_id: u32,
+    /// Head descriptor of the transfer queued in each period slot
+    slots: [Option<u16>; NUM_PERIODS],
 }
 
 impl VirtioSoundDevice {
-    /// Create a new VirtIO Sound device instance
-    pub fn new(mmio_base: usize) -> Self {
-        Self {
-            pci_device_id: 0,
-            mmio_base,
-            control_queue: 0,
-            event_queue: 1,
-            tx_queue: 2,
-            rx_queue: 3,
-            num_jacks: 0,
-            num_streams: 0,
-            num_chmaps: 0,
-            initialized: false,
-            tx_buffer: Vec::new(),
-            device_name: String::from("VirtIO Sound Device"),
-            device_capabilities: AudioDeviceCapabilities {
-                min_sample_rate: 5512,
-                max_sample_rate: 192000,
-                min_channels: 1,
-                max_channels: 8,
-                supported_formats: vec![
-                    SampleFormat::U8,
-                    SampleFormat::S16Le,
-                    SampleFormat::S16Be,
-                    SampleFormat::S24Le,
-                    SampleFormat::S32Le,
-                    SampleFormat::F32,
-                ],
-                playback: true,
-                capture: true,
-            },
-            active_stream_id: 0,
-            current_config: None,
-            stream_running: false,
-        }
-    }
-
-    /// Initialize the VirtIO Sound device
-    ///
-    /// Performs device reset, feature negotiation, virtqueue setup, and
-    /// queries the device configuration for available jacks, streams,
-    /// and channel maps.
-    pub fn init(&mut self) -> Result<(), KernelError> {
-        if self.mmio_base == 0 {
-            return Err(KernelError::InvalidArgument {
-                name: "mmio_base",
-                value: "zero address",
+    /// Initialize the device behind `transport` and start playback on its
+    /// first suitable output stream.
+    pub fn new(transport: VirtioMmioTransport) -> Result<Self, KernelError> {
+        if !transport.matches_device(VIRTIO_ID_SOUND) {
+            return Err(KernelError::HardwareError {
+                device: "virtio-snd",
+                code: 0xdead0001,
             });
         }
 
-        println!(
-            "[AUDIO] VirtIO-Sound: initializing at MMIO 0x{:x}",
-            self.mmio_base
-        );
-
-        // Step 1: Reset device
-        self.write_reg(VIRTIO_MMIO_STATUS, 0);
-
-        // Step 2: Set ACKNOWLEDGE status bit
-        self.write_reg(VIRTIO_MMIO_STATUS, VIRTIO_STATUS_ACKNOWLEDGE);
-
-        // Step 3: Set DRIVER status bit
-        self.write_reg(
-            VIRTIO_MMIO_STATUS,
-            VIRTIO_STATUS_ACKNOWLEDGE | VIRTIO_STATUS_DRIVER,
-        );
-
-        // Step 4: Read device configuration
-        self.num_jacks = self.read_config(0);
-        self.num_streams = self.read_config(4);
-        self.num_chmaps = self.read_config(8);
-
-        println!(
-            "[AUDIO] VirtIO-Sound: {} jacks, {} streams, {} channel maps",
-            self.num_jacks, self.num_streams, self.num_chmaps
-        );
-
-        // Step 5: Set FEATURES_OK
-        self.write_reg(
-            VIRTIO_MMIO_STATUS,
-            VIRTIO_STATUS_ACKNOWLEDGE | VIRTIO_STATUS_DRIVER | VIRTIO_STATUS_FEATURES_OK,
-        );
-
-        // Step 6: Set DRIVER_OK to complete initialization
-        self.write_reg(
-            VIRTIO_MMIO_STATUS,
-            VIRTIO_STATUS_ACKNOWLEDGE
-                | VIRTIO_STATUS_DRIVER
-                | VIRTIO_STATUS_FEATURES_OK
-                | VIRTIO_STATUS_DRIVER_OK,
-        );
-
-        self.initialized = true;
-        println!("[AUDIO] VirtIO-Sound: device initialized");
-        Ok(())
-    }
-
-    /// Configure a PCM stream with the given audio parameters
-    pub fn configure_stream(
-        &mut self,
-        stream_id: u32,
-        config: &AudioConfig,
-    ) -> Result<(), KernelError> {
-        if !self.initialized {
-            return Err(KernelError::InvalidState {
-                expected: "initialized",
-                actual: "not initialized",
-            });
+        transport.begin_init();
+        let _ = transport.read_device_features();
+        transport.write_driver_features(0);
+        if transport.version() >= 2 {
+            let _ = transport.read_device_features_high();
+            transport.write_driver_features_high(VIRTIO_F_VERSION_1_HIGH);
         }
-
-        if stream_id >= self.num_streams {
-            return Err(KernelError::InvalidArgument {
-                name: "stream_id",
-                value: "exceeds device stream count",
+        if !transport.set_features_ok() {
+            transport.set_failed();
+            return Err(KernelError::HardwareError {
+                device: "virtio-snd",
+                code: 0x04,
             });
         }
 
-        let format = match config.format {
-            SampleFormat::U8 => VIRTIO_SND_PCM_FMT_U8,
-            SampleFormat::S16Le => VIRTIO_SND_PCM_FMT_S16,
-            SampleFormat::S16Be => VIRTIO_SND_PCM_FMT_S16_BE,
-            SampleFormat::S24Le => VIRTIO_SND_PCM_FMT_S24,
-            SampleFormat::S32Le => VIRTIO_SND_PCM_FMT_S32,
-            SampleFormat::F32 => VIRTIO_SND_PCM_FMT_FLOAT,
-        };
+        let control = Self::setup_queue(&transport, CONTROL_QUEUE)?;
+        let tx = Self::setup_queue(&transport, TX_QUEUE)?;
 
-        let rate = match config.sample_rate {
-            5512 => VIRTIO_SND_PCM_RATE_5512,
-            8000 => VIRTIO_SND_PCM_RATE_8000,
-            11025 => VIRTIO_SND_PCM_RATE_11025,
-            16000 => VIRTIO_SND_PCM_RATE_16000,
-            22050 => VIRTIO_SND_PCM_RATE_22050,
-            32000 => VIRTIO_SND_PCM_RATE_32000,
-            44100 => VIRTIO_SND_PCM_RATE_44100,
-            48000 => VIRTIO_SND_PCM_RATE_48000,
-            96000 => VIRTIO_SND_PCM_RATE_96000,
-            192000 => VIRTIO_SND_PCM_RATE_192000,
-            _ => {
-                return Err(KernelError::InvalidArgument {
-                    name: "sample_rate",
-                    value: "unsupported sample rate",
-                });
-            }
+        let frames = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(DMA_FRAMES, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: DMA_FRAMES * FRAME_SIZE,
+                available: 0,
+            })?;
+        let dma_phys = frames.as_u64() * FRAME_SIZE as u64;
+        let dma_virt = crate::mm::phys_to_virt_addr(dma_phys) as usize;
+
+        transport.set_driver_ok();
+
+        let mut device = Self {
+            transport,
+            control,
+            tx,
+            frames,
+            dma_phys,
+            dma_virt,
+            stream_id: 0,
+            slots: [None; NUM_PERIODS],
         };
+        device.start_playback()?;
+        Ok(device)
+    }
 
-        let frame_size = config.frame_size() as u32;
-        let period_bytes = config.buffer_frames * frame_size;
-        let buffer_bytes = period_bytes * 4; // 4 periods
+    fn setup_queue(transport: &VirtioMmioTransport, index: u16) -> Result<VirtQueue, KernelError> {
+        transport.select_queue(index);
+        let size = transport.read_queue_size_max();
+        if size == 0 {
+            transport.set_failed();
+            return Err(KernelError::HardwareError {
+                device: "virtio-snd",
+                code: 0x01,
+            });
+        }
+        let queue = VirtQueue::new(size)?;
+        transport.set_queue_size(queue.size());
+        transport.write_queue_phys(queue.phys_desc(), queue.phys_avail(), queue.phys_used());
+        transport.set_queue_ready();
+        Ok(queue)
+    }
 
-        let _params = VirtioSndPcmSetParams {
-            hdr: VirtioSndPcmHdr {
+    /// Find an output stream, configure it for the pipeline output and
+    /// start it.
+    fn start_playback(&mut self) -> Result<(), KernelError> {
+        let num_streams = self.transport.read_config_u32(4) as usize;
+        let count = num_streams.min(MAX_STREAMS);
+        let info_size = core::mem::size_of::<VirtioSndPcmInfo>();
+        self.control_request(
+            VirtioSndQueryInfo {
                 hdr: VirtioSndHdr {
-                    code: VIRTIO_SND_R_PCM_SET_PARAMS,
+                    code: VIRTIO_SND_R_PCM_INFO,
                 },
-                stream_id,
+                start_id: 0,
+                count: count as u32,
+                size: info_size as u32,
             },
-            buffer_bytes,
-            period_bytes,
-            features: 0,
-            channels: config.channels,
-            format,
-            rate,
-            _padding: 0,
+            count * info_size,
+        )?;
+
+        let mut infos = Vec::with_capacity(count);
+        for i in 0..count {
+            let at = self.dma_virt + CONTROL_RESPONSE + 4 + i * info_size;
+            // SAFETY: The device wrote `count` info entries after the response
+            // header; `at` stays within the control frame.
+            infos.push(unsafe { core::ptr::read_unaligned(at as *const VirtioSndPcmInfo) });
+        }
+        self.stream_id = find_output_stream(&infos).ok_or(KernelError::NotFound {
+            resource: "virtio-snd S16 48 kHz stereo output stream",
+            id: 0,
+        })?;
+
+        let hdr = VirtioSndPcmHdr {
+            hdr: VirtioSndHdr {
+                code: VIRTIO_SND_R_PCM_SET_PARAMS,
+            },
+            stream_id: self.stream_id,
         };
+        self.control_request(
+            VirtioSndPcmSetParams {
+                hdr,
+                buffer_bytes: (PERIOD_BYTES * NUM_PERIODS) as u32,
+                period_bytes: PERIOD_BYTES as u32,
+                features: 0,
+                channels: 2,
+                format: VIRTIO_SND_PCM_FMT_S16,
+                rate: VIRTIO_SND_PCM_RATE_48000,
+                _padding: 0,
+            },
+            0,
+        )?;
+        for code in [VIRTIO_SND_R_PCM_PREPARE, VIRTIO_SND_R_PCM_START] {
+            self.control_request(
+                VirtioSndPcmHdr {
+                    hdr: VirtioSndHdr { code },
+                    stream_id: self.stream_id,
+                },
+                0,
+            )?;
+        }
 
-        // In a real driver, we would submit this to the control virtqueue
-        // and wait for a response. For now, log the configuration.
         println!(
-            "[AUDIO] VirtIO-Sound: configured stream {} ({} Hz, {} ch, fmt {})",
-            stream_id, config.sample_rate, config.channels, format
+            "[AUDIO] VirtIO-Sound: playing on stream {} of {}",
+            self.stream_id, num_streams
         );
-
-        // Send PREPARE command
-        self.send_pcm_command(stream_id, VIRTIO_SND_R_PCM_PREPARE)?;
-
         Ok(())
     }
 
-    /// Start a PCM stream
-    pub fn start_stream(&mut self, stream_id: u32) -> Result<(), KernelError> {
-        if !self.initialized {
-            return Err(KernelError::InvalidState {
-                expected: "initialized",
-                actual: "not initialized",
-            });
+    /// Send a control request and wait for its response, which carries
+    /// `payload_len` bytes after the status header.
+    fn control_request<T: Copy>(
+        &mut self,
+        request: T,
+        payload_len: usize,
+    ) -> Result<(), KernelError> {
+        let request_len = core::mem::size_of::<T>();
+        // SAFETY: The control frame is ours; the request and the response
+        // area after it both fit into it.
+        unsafe {
+            core::ptr::write_unaligned(self.dma_virt as *mut T, request);
+            core::ptr::write_volatile((self.dma_virt + CONTROL_RESPONSE) as *mut u32, 0);
         }
 
-        self.send_pcm_command(stream_id, VIRTIO_SND_R_PCM_START)?;
-        println!("[AUDIO] VirtIO-Sound: stream {} started", stream_id);
-        Ok(())
-    }
-
-    /// Stop a PCM stream
-    pub fn stop_stream(&mut self, stream_id: u32) -> Result<(), KernelError> {
-        if !self.initialized {
-            return Err(KernelError::InvalidState {
-                expected: "initialized",
-                actual: "not initialized",
+        let head = self.control.alloc_desc();
+        let response = self.control.alloc_desc();
+        let (Some(head), Some(response)) = (head, response) else {
+            if let Some(desc) = head.or(response) {
+                self.control.free_desc(desc);
+            }
+            return Err(KernelError::ResourceExhausted {
+                resource: "virtio-snd control descriptors",
             });
+        };
+        // SAFETY: Both descriptors were just allocated and point into the
+        // control frame, which outlives the request.
+        unsafe {
+            self.control.write_desc(
+                head,
+                self.dma_phys,
+                request_len as u32,
+                VIRTQ_DESC_F_NEXT,
+                response,
+            );
+            self.control.write_desc(
+                response,
+                self.dma_phys + CONTROL_RESPONSE as u64,
+                (4 + payload_len) as u32,
+                VIRTQ_DESC_F_WRITE,
+                0,
+            );
         }
-
-        self.send_pcm_command(stream_id, VIRTIO_SND_R_PCM_STOP)?;
-        println!("[AUDIO] VirtIO-Sound: stream {} stopped", stream_id);
-        Ok(())
-    }
-
-    /// Write PCM data to a stream's TX queue
-    ///
-    /// Returns the number of samples actually queued for playback.
-    pub fn write_pcm(&mut self, stream_id: u32, data: &[i16]) -> Result<usize, KernelError> {
-        if !self.initialized {
-            return Err(KernelError::InvalidState {
-                expected: "initialized",
-                actual: "not initialized",
-            });
+        atomic::fence(Ordering::Release);
+        self.control.push_avail(head);
+        self.transport.notify_queue(CONTROL_QUEUE);
+
+        let mut spins: u32 = 0;
+        while !self.control.has_used() {
+            core::hint::spin_loop();
+            spins += 1;
+            if spins >= MAX_SPINS {
+                return Err(KernelError::Timeout {
+                    operation: "virtio-snd control request",
+                    duration_ms: 0,
+                });
+            }
         }
-
-        if stream_id >= self.num_streams {
-            return Err(KernelError::InvalidArgument {
-                name: "stream_id",
-                value: "exceeds device stream count",
+        let _ = self.control.poll_used();
+        self.control.free_chain(head);
+
+        // SAFETY: The device has returned the response buffer.
+        let status =
+            unsafe { core::ptr::read_volatile((self.dma_virt + CONTROL_RESPONSE) as *const u32) };
+        if status != VIRTIO_SND_S_OK {
+            return Err(KernelError::HardwareError {
+                device: "virtio-snd",
+                code: status,
             });
         }
-
-        // Build TX buffer: VirtioSndPcmXfer header + PCM data
-        let header = VirtioSndPcmXfer { stream_id };
-        // SAFETY: Reinterpreting a #[repr(C)] struct as a byte slice for
-        // serialization into the TX buffer.
-        let header_bytes = unsafe {
-            core::slice::from_raw_parts(
-                &header as *const VirtioSndPcmXfer as *const u8,
-                core::mem::size_of::<VirtioSndPcmXfer>(),
-            )
-        };
-
-        // SAFETY: Reinterpreting an i16 slice as a byte slice. The data
-        // pointer is valid and the byte length is exactly data.len() * 2.
-        let pcm_bytes =
-            unsafe { core::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * 2) };
-
-        // Accumulate in TX buffer (real driver would submit to virtqueue)
-        self.tx_buffer.clear();
-        self.tx_buffer.extend_from_slice(header_bytes);
-        self.tx_buffer.extend_from_slice(pcm_bytes);
-
-        Ok(data.len())
-    }
-
-    /// Check if a VirtIO Sound device is available on the PCI bus
-    pub fn is_available() -> bool {
-        // Scan PCI bus for VirtIO Sound device
-        // This would use the PCI subsystem in a real implementation
-        false
-    }
-
-    // ========================================================================
-    // Private helpers
-    // ========================================================================
-
-    /// Send a simple PCM command (no extra parameters)
-    fn send_pcm_command(&self, stream_id: u32, command: u32) -> Result<(), KernelError> {
-        let _cmd = VirtioSndPcmHdr {
-            hdr: VirtioSndHdr { code: command },
-            stream_id,
-        };
-
-        // In a real driver, submit to control virtqueue and poll response.
-        // For now, simulate success.
         Ok(())
     }
 
-    /// Write to a VirtIO MMIO register
-    fn write_reg(&self, offset: u32, value: u32) {
-        if self.mmio_base == 0 {
-            return;
-        }
-        let addr = self.mmio_base + offset as usize;
-        // SAFETY: MMIO register write at device address validated non-zero above.
-        unsafe {
-            core::ptr::write_volatile(addr as *mut u32, value);
-        }
-    }
-
-    /// Read from a VirtIO MMIO register
-    fn read_reg(&self, offset: u32) -> u32 {
-        if self.mmio_base == 0 {
-            return 0;
+    /// Free the slots of all periods the device has finished playing.
+    fn reclaim(&mut self) {
+        while let Some((head, _)) = self.tx.poll_used() {
+            if let Some(slot) = self.slots.iter_mut().find(|slot| **slot == Some(head)) {
+                *slot = None;
+            }
+            self.tx.free_chain(head);
         }
-        let addr = self.mmio_base + offset as usize;
-        // SAFETY: MMIO register read at device address validated non-zero above.
-        unsafe { core::ptr::read_volatile(addr as *const u32) }
-    }
-
-    /// Read from device-specific configuration space
-    fn read_config(&self, offset: u32) -> u32 {
-        self.read_reg(VIRTIO_MMIO_CONFIG + offset)
     }
 }
 
-// ============================================================================
-// AudioDevice Trait Implementation for VirtioSoundDevice
-// ============================================================================
-
-impl AudioDevice for VirtioSoundDevice {
-    fn configure(&mut self, config: &AudioConfig) -> Result<AudioConfig, AudioError> {
-        self.configure_stream(self.active_stream_id, config)
-            .map_err(|_| AudioError::InvalidConfig {
-                reason: "VirtIO stream configuration failed",
-            })?;
-        self.current_config = Some(*config);
-        Ok(*config)
+impl OutputSink for VirtioSoundDevice {
+    fn name(&self) -> &'static str {
+        "VirtIO Sound"
     }
 
-    fn start(&mut self) -> Result<(), AudioError> {
-        if self.stream_running {
-            return Err(AudioError::AlreadyStarted);
-        }
-        self.start_stream(self.active_stream_id)
-            .map_err(|_| AudioError::IoError)?;
-        self.stream_running = true;
-        Ok(())
-    }
-
-    fn stop(&mut self) -> Result<(), AudioError> {
-        if !self.stream_running {
-            return Err(AudioError::NotStarted);
-        }
-        self.stop_stream(self.active_stream_id)
-            .map_err(|_| AudioError::IoError)?;
-        self.stream_running = false;
-        Ok(())
+    fn writable_frames(&mut self) -> usize {
+        self.reclaim();
+        let free = self.slots.iter().filter(|slot| slot.is_none()).count();
+        free * PERIOD_BYTES / AudioConfig::default_config().frame_size() as usize
     }
 
-    fn write_frames(&mut self, data: &[u8]) -> Result<usize, AudioError> {
-        let config = self.current_config.ok_or(AudioError::InvalidConfig {
-            reason: "stream not configured",
-        })?;
-        let frame_size = config.frame_size() as usize;
-        if frame_size == 0 {
+    fn write(&mut self, samples: &[i16]) -> Result<usize, AudioError> {
+        let bytes = (samples.len() * 2).min(PERIOD_BYTES);
+        if bytes == 0 {
             return Ok(0);
         }
-
-        // Convert byte data to i16 slice for write_pcm
-        // write_pcm expects &[i16], so we need an even number of bytes
-        let aligned_len = data.len() & !1; // round down to even
-        if aligned_len == 0 {
-            return Ok(0);
+        self.reclaim();
+        let slot = self
+            .slots
+            .iter()
+            .position(Option::is_none)
+            .ok_or(AudioError::BufferOverrun)?;
+
+        let meta = 16 * slot;
+        let meta_phys = self.dma_phys + FRAME_SIZE as u64 + meta as u64;
+        let meta_virt = self.dma_virt + FRAME_SIZE + meta;
+        let data_phys = self.dma_phys + ((2 + slot) * FRAME_SIZE) as u64;
+        let data_virt = self.dma_virt + (2 + slot) * FRAME_SIZE;
+        // SAFETY: The slot is free, so the device no longer reads its header
+        // or data frame; both lie within our DMA frames.
+        unsafe {
+            core::ptr::write_volatile(
+                meta_virt as *mut VirtioSndPcmXfer,
+                VirtioSndPcmXfer {
+                    stream_id: self.stream_id,
+                },
+            );
+            core::ptr::copy_nonoverlapping(
+                samples.as_ptr() as *const u8,
+                data_virt as *mut u8,
+                bytes,
+            );
         }
 
-        // SAFETY: Reinterpreting a u8 slice as i16. The data pointer is valid,
-        // aligned_len is even, and i16 has alignment <= u8 slice alignment.
-        let samples =
-            unsafe { core::slice::from_raw_parts(data.as_ptr() as *const i16, aligned_len / 2) };
-
-        let written_samples = self
-            .write_pcm(self.active_stream_id, samples)
-            .map_err(|_| AudioError::IoError)?;
-
-        // Convert samples written back to frames
-        let channels = config.channels as usize;
-        if channels == 0 {
-            return Ok(0);
+        let mut descs = [0u16; 3];
+        for i in 0..descs.len() {
+            match self.tx.alloc_desc() {
+                Some(desc) => descs[i] = desc,
+                None => {
+                    for &desc in &descs[..i] {
+                        self.tx.free_desc(desc);
+                    }
+                    return Err(AudioError::BufferOverrun);
+                }
+            }
         }
-        Ok(written_samples / channels)
-    }
-
-    fn read_frames(&mut self, _output: &mut [u8]) -> Result<usize, AudioError> {
-        // VirtIO Sound capture would use the rx_queue; not yet implemented
-        // in the underlying driver, so return an error.
-        Err(AudioError::UnsupportedFormat)
-    }
-
-    fn capabilities(&self) -> &AudioDeviceCapabilities {
-        &self.device_capabilities
-    }
-
-    fn name(&self) -> &str {
-        &self.device_name
-    }
+        // SAFETY: The descriptors were just allocated; header, data and
+        // status buffers stay valid until the device returns the chain.
+        unsafe {
+            self.tx
+                .write_desc(descs[0], meta_phys, 4, VIRTQ_DESC_F_NEXT, descs[1]);
+            self.tx.write_desc(
+                descs[1],
+                data_phys,
+                bytes as u32,
+                VIRTQ_DESC_F_NEXT,
+                descs[2],
+            );
+            self.tx.write_desc(
+                descs[2],
+                meta_phys + 8,
+                core::mem::size_of::<VirtioSndPcmStatus>() as u32,
+                VIRTQ_DESC_F_WRITE,
+                0,
+            );
+        }
+        atomic::fence(Ordering::Release);
+        self.tx.push_avail(descs[0]);
+        self.transport.notify_queue(TX_QUEUE);
+        self.slots[slot] = Some(descs[0]);
 
-    fn is_playback(&self) -> bool {
-        true
+        Ok(bytes / AudioConfig::default_config().frame_size() as usize)
     }
+}
 
-    fn is_capture(&self) -> bool {
-        true
+impl Drop for VirtioSoundDevice {
+    fn drop(&mut self) {
+        // Stop the device from touching the buffers before freeing them
+        self.transport.set_failed();
+        let _ = FRAME_ALLOCATOR.lock().free_frames(self.frames, DMA_FRAMES);
     }
 }
 
 // ============================================================================
-// VirtIO MMIO Transport Constants
+// Probing
 // ============================================================================
 
-/// MMIO register offsets
-const VIRTIO_MMIO_STATUS: u32 = 0x070;
-const VIRTIO_MMIO_CONFIG: u32 = 0x100;
-
-/// Device status bits
-const VIRTIO_STATUS_ACKNOWLEDGE: u32 = 1;
-const VIRTIO_STATUS_DRIVER: u32 = 2;
-const VIRTIO_STATUS_FEATURES_OK: u32 = 8;
-const VIRTIO_STATUS_DRIVER_OK: u32 = 4;
-
-// ============================================================================
-// Global Driver State
-// ============================================================================
-
-static VIRTIO_SOUND: spin::Mutex<Option<VirtioSoundDevice>> = spin::Mutex::new(None);
-
-/// Initialize the VirtIO Sound driver
-///
-/// Scans the PCI bus for a VirtIO Sound device (vendor 0x1AF4, device 0x1059)
-/// and initializes it if found.
-pub fn init() -> Result<(), KernelError> {
-    // Scan PCI for VirtIO Sound device
-    if !scan_pci_for_virtio_sound() {
-        return Err(KernelError::NotFound {
-            resource: "VirtIO Sound device",
-            id: 0,
-        });
-    }
-
-    Ok(())
-}
-
-/// Access the global VirtIO Sound device through a closure
-pub fn with_device<R, F: FnOnce(&mut VirtioSoundDevice) -> R>(f: F) -> Result<R, KernelError> {
-    let mut guard = VIRTIO_SOUND.lock();
-    match guard.as_mut() {
-        Some(device) => Ok(f(device)),
-        None => Err(KernelError::NotInitialized {
-            subsystem: "VirtIO Sound",
-        }),
-    }
-}
-
-/// Scan PCI bus for a VirtIO Sound device
-///
-/// Iterates the PCI configuration space looking for vendor 0x1AF4 with
-/// device ID 0x1059. If found, reads the BAR0 MMIO address, creates
-/// the device, and initializes it.
-fn scan_pci_for_virtio_sound() -> bool {
-    // Iterate PCI buses, devices, functions
-    for bus in 0u8..=255 {
-        for device in 0u8..32 {
-            for function in 0u8..8 {
-                let vendor_device = pci_config_read(bus, device, function, 0);
-                let vendor = (vendor_device & 0xFFFF) as u16;
-                let dev_id = ((vendor_device >> 16) & 0xFFFF) as u16;
-
-                if vendor == VIRTIO_SND_PCI_VENDOR && dev_id == VIRTIO_SND_PCI_DEVICE {
-                    println!(
-                        "[AUDIO] VirtIO-Sound: found at PCI {:02x}:{:02x}.{:x}",
-                        bus, device, function
-                    );
-
-                    // Read BAR0 for MMIO base address
-                    let bar0 = pci_config_read(bus, device, function, 0x10);
-                    let mmio_base = (bar0 & 0xFFFFFFF0) as usize;
-
-                    if mmio_base == 0 {
-                        println!("[AUDIO] VirtIO-Sound: BAR0 is zero, skipping");
-                        continue;
-                    }
-
-                    let mut snd_device = VirtioSoundDevice::new(mmio_base);
-                    snd_device.pci_device_id = vendor_device;
-
-                    match snd_device.init() {
-                        Ok(()) => {
-                            *VIRTIO_SOUND.lock() = Some(snd_device);
-                            return true;
-                        }
-                        Err(e) => {
-                            println!("[AUDIO] VirtIO-Sound: init failed: {:?}", e);
-                            return false;
-                        }
-                    }
-                }
-
-                // Check if device is multi-function
-                if function == 0 {
-                    let header_type = (pci_config_read(bus, device, function, 0x0C) >> 16) & 0xFF;
-                    if header_type & 0x80 == 0 {
-                        break; // Not multi-function
-                    }
-                }
+/// Find and initialize a virtio-mmio sound device
+pub fn probe() -> Option<VirtioSoundDevice> {
+    for base in crate::drivers::virtio::mmio::device_bases() {
+        let transport = VirtioMmioTransport::new(base);
+        if !transport.matches_device(VIRTIO_ID_SOUND) {
+            continue;
+        }
+        match VirtioSoundDevice::new(transport) {
+            Ok(device) => {
+                println!("[AUDIO] VirtIO-Sound: device at {:#x}", base);
+                return Some(device);
+            }
+            Err(_e) => {
+                println!("[AUDIO] VirtIO-Sound: init at {:#x} failed: {:?}", base, _e);
             }
         }
     }
-
-    false
-}
-
-/// Read a 32-bit value from PCI configuration space
-///
-/// Uses x86 I/O port-based PCI config access (0xCF8/0xCFC).
-fn pci_config_read(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
-    let address: u32 = (1u32 << 31)
-        | ((bus as u32) << 16)
-        | ((device as u32) << 11)
-        | ((function as u32) << 8)
-        | ((offset as u32) & 0xFC);
-
-    #[cfg(target_arch = "x86_64")]
-    // SAFETY: Port I/O to PCI configuration space registers (0xCF8/0xCFC).
-    // These are well-known x86 I/O ports for PCI bus enumeration.
-    unsafe {
-        // Write address to CONFIG_ADDRESS (0xCF8)
-        core::arch::asm!(
-            "out dx, eax",
-            in("dx") 0xCF8u16,
-            in("eax") address,
-            options(nomem, nostack)
-        );
-        // Read data from CONFIG_DATA (0xCFC)
-        let value: u32;
-        core::arch::asm!(
-            "in eax, dx",
-            in("dx") 0xCFCu16,
-            out("eax") value,
-            options(nomem, nostack)
-        );
-        value
-    }
-
-    #[cfg(not(target_arch = "x86_64"))]
-    {
-        let _ = (address, bus, device, function, offset);
-        0xFFFFFFFF // No device present on non-x86
-    }
+    None
 }
 
 // ============================================================================
@@ -808,41 +659,81 @@ fn pci_config_read(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
 mod tests {
     use super::*;
 
+    fn info(direction: u8, formats: u64, rates: u64, channels: (u8, u8)) -> VirtioSndPcmInfo {
+        VirtioSndPcmInfo {
+            hda_fn_nid: 0,
+            features: 0,
+            formats,
+            rates,
+            direction,
+            channels_min: channels.0,
+            channels_max: channels.1,
+            _padding: [0; 5],
+        }
+    }
+
     #[test]
     fn test_virtio_snd_hdr_size() {
         assert_eq!(core::mem::size_of::<VirtioSndHdr>(), 4);
     }
 
     #[test]
-    fn test_virtio_snd_pcm_set_params_size() {
+    fn test_virtio_snd_struct_sizes() {
         assert_eq!(core::mem::size_of::<VirtioSndPcmSetParams>(), 24);
+        assert_eq!(core::mem::size_of::<VirtioSndPcmInfo>(), 32);
+        assert_eq!(core::mem::size_of::<VirtioSndPcmXfer>(), 4);
+        assert_eq!(core::mem::size_of::<VirtioSndPcmStatus>(), 8);
+        assert_eq!(core::mem::size_of::<VirtioSndQueryInfo>(), 16);
     }
 
     #[test]
-    fn test_device_creation() {
-        let device = VirtioSoundDevice::new(0);
-        assert!(!device.initialized);
-        assert_eq!(device.num_streams, 0);
-        assert_eq!(device.num_jacks, 0);
+    fn test_format_constants() {
+        assert_eq!(VIRTIO_SND_PCM_FMT_U8, 4);
+        assert_eq!(VIRTIO_SND_PCM_FMT_S16, 5);
+        assert_eq!(VIRTIO_SND_PCM_FMT_FLOAT, 19);
+        assert_eq!(VIRTIO_SND_PCM_RATE_44100, 6);
+        assert_eq!(VIRTIO_SND_PCM_RATE_48000, 7);
+        assert_eq!(VIRTIO_SND_PCM_RATE_384000, 13);
     }
 
     #[test]
-    fn test_device_init_zero_mmio() {
-        let mut device = VirtioSoundDevice::new(0);
-        let result = device.init();
-        assert!(result.is_err());
+    fn test_status_constants() {
+        assert_eq!(VIRTIO_SND_S_OK, 0x8000);
+        assert_eq!(VIRTIO_SND_S_IO_ERR, 0x8003);
     }
 
     #[test]
-    fn test_format_constants() {
-        assert_eq!(VIRTIO_SND_PCM_FMT_S16, 2);
-        assert_eq!(VIRTIO_SND_PCM_RATE_44100, 8);
-        assert_eq!(VIRTIO_SND_PCM_RATE_48000, 9);
+    fn test_find_output_stream() {
+        let s16 = 1 << VIRTIO_SND_PCM_FMT_S16;
+        let r48k = 1 << VIRTIO_SND_PCM_RATE_48000;
+        let infos = [
+            info(VIRTIO_SND_D_INPUT, s16, r48k, (1, 2)),
+            info(
+                VIRTIO_SND_D_OUTPUT,
+                1 << VIRTIO_SND_PCM_FMT_U8,
+                r48k,
+                (1, 2),
+            ),
+            info(
+                VIRTIO_SND_D_OUTPUT,
+                s16,
+                1 << VIRTIO_SND_PCM_RATE_44100,
+                (1, 2),
+            ),
+            info(VIRTIO_SND_D_OUTPUT, s16, r48k, (1, 1)),
+            info(VIRTIO_SND_D_OUTPUT, s16 | 1, r48k | 1, (1, 8)),
+        ];
+        assert_eq!(find_output_stream(&infos), Some(4));
+        assert_eq!(find_output_stream(&infos[..4]), None);
     }
 
     #[test]
-    fn test_status_constants() {
-        assert_eq!(VIRTIO_SND_S_OK, 0x8000);
-        assert_eq!(VIRTIO_SND_S_IO_ERR, 0x8003);
+    fn test_period_matches_pipeline() {
+        let config = AudioConfig::default_config();
+        assert_eq!(
+            PERIOD_BYTES,
+            config.buffer_frames as usize * config.frame_size() as usize
+        );
+        assert!(PERIOD_BYTES <= FRAME_SIZE);
     }
 }
@@ -322,6 +322,37 @@ pub fn convert_s32_to_s16(data: &[u8]) -> Vec<i16> {
     output
 }
 
+/// Resample interleaved S16 PCM from `from_rate` to `to_rate` Hz
+///
+/// Interpolates linearly between neighbouring frames. Good enough for
+/// playing back 44.1 kHz files on the 48 kHz output; not a band-limited
+/// resampler.
+pub fn resample_linear(samples: &[i16], channels: usize, from_rate: u32, to_rate: u32) -> Vec<i16> {
+    if channels == 0 || from_rate == 0 || to_rate == 0 || from_rate == to_rate {
+        return samples.to_vec();
+    }
+    let in_frames = samples.len() / channels;
+    if in_frames == 0 {
+        return Vec::new();
+    }
+    let out_frames = (in_frames as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let mut output = Vec::with_capacity(out_frames * channels);
+
+    for frame in 0..out_frames {
+        // Source position in 16.16 fixed point
+        let pos = ((frame as u64 * from_rate as u64) << 16) / to_rate as u64;
+        let index = (pos >> 16) as usize;
+        let frac = (pos & 0xFFFF) as i32;
+        let next = (index + 1).min(in_frames - 1);
+        for ch in 0..channels {
+            let a = samples[index * channels + ch] as i32;
+            let b = samples[next * channels + ch] as i32;
+            output.push((a + (((b - a) * frac) >> 16)) as i16);
+        }
+    }
+    output
+}
+
 // ============================================================================
 // Little-Endian Reading Helpers
 // ============================================================================
@@ -483,6 +514,20 @@ mod tests {
         assert_eq!(result[0], 0x7F); // 0x007FFF >> 8 = 0x7F
     }
 
+    #[test]
+    fn test_resample_linear() {
+        // Same rate: unchanged
+        assert_eq!(resample_linear(&[1, 2, 3], 1, 48000, 48000), [1, 2, 3]);
+
+        // Doubling the rate interpolates between frames
+        let out = resample_linear(&[0, 0, 100, -100], 2, 24000, 48000);
+        assert_eq!(out, [0, 0, 50, -50, 100, -100, 100, -100]);
+
+        // 44.1 kHz -> 48 kHz keeps the duration
+        let out = resample_linear(&[0i16; 441], 1, 44100, 48000);
+        assert_eq!(out.len(), 480);
+    }
+
     #[test]
     fn test_total_samples() {
         let pcm = [0u8; 40]; // 40 bytes
@@ -0,0 +1,497 @@
+//! Filesystem benchmarks and stress tests
+//!
+//! Drives a filesystem through the VFS node interface, the way the file
+//! syscalls do, and reports throughput and per-operation latency
+//! percentiles for:
+//! - sequential write and read of one large file in `io_size` chunks
+//! - random writes and reads at `io_size`-aligned offsets of that file
+//! - metadata churn: create + small write, then unlink, of many files
+//! - several readers streaming their own files a chunk at a time in turn
+//!
+//! Write tests include the final `sync`, so the time to push dirty blocks
+//! through the block cache to the device is part of their throughput.
+//! Every read checks the data against the pattern it was written with, so
+//! a run doubles as a stress test of the cache and write-back paths.
+//!
+//! By default the suite runs on a scratch BlockFS backed by a private RAM
+//! disk; [`Target::Directory`] runs it in a directory of a mounted
+//! filesystem instead. Results are printed by the `bench fs` shell command.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+
+use spin::Mutex;
+
+use super::{cycles_to_ns, read_timestamp, LatencySummary};
+use crate::{
+    error::{FsError, KernelError},
+    fs::{
+        blockdev::RamBlockDevice,
+        blockfs::{BlockDeviceBackend, BlockFs, BLOCK_SIZE},
+        Filesystem, Permissions, VfsNode,
+    },
+};
+
+/// Parameters of a benchmark run
+#[derive(Debug, Clone, Copy)]
+pub struct FsBenchConfig {
+    /// Size of the file the sequential and random tests use
+    pub file_size: usize,
+    /// Bytes per read or write call
+    pub io_size: usize,
+    /// Operations per random test
+    pub random_ops: usize,
+    /// Files the metadata test creates and deletes
+    pub metadata_files: usize,
+    /// Readers in the concurrent read test
+    pub readers: usize,
+}
+
+impl Default for FsBenchConfig {
+    fn default() -> Self {
+        Self {
+            file_size: 4 * 1024 * 1024,
+            io_size: BLOCK_SIZE,
+            random_ops: 1024,
+            metadata_files: 256,
+            readers: 4,
+        }
+    }
+}
+
+/// Where the benchmark files are created
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// A fresh BlockFS on a RAM disk, discarded afterwards
+    Scratch,
+    /// An existing directory; the files are removed afterwards
+    Directory(String),
+}
+
+/// Outcome of one test
+#[derive(Debug, Clone)]
+pub struct FsBenchResult {
+    pub name: &'static str,
+    /// Operations timed individually
+    pub ops: u64,
+    /// Bytes moved (0 for metadata tests)
+    pub bytes: u64,
+    /// Wall time of the whole test, including any sync
+    pub total_ns: u64,
+    pub latency: LatencySummary,
+}
+
+impl FsBenchResult {
+    fn new(name: &'static str, bytes: u64, total_ns: u64, samples: &mut [u64]) -> Self {
+        Self {
+            name,
+            ops: samples.len() as u64,
+            bytes,
+            total_ns,
+            latency: LatencySummary::from_samples(samples),
+        }
+    }
+
+    /// Throughput in KiB per second (0 for metadata tests).
+    pub fn kib_per_sec(&self) -> u64 {
+        per_second(self.bytes / 1024, self.total_ns)
+    }
+
+    /// Operations per second.
+    pub fn ops_per_sec(&self) -> u64 {
+        per_second(self.ops, self.total_ns)
+    }
+}
+
+fn per_second(count: u64, ns: u64) -> u64 {
+    if ns == 0 {
+        return 0;
+    }
+    (count as u128 * 1_000_000_000 / ns as u128) as u64
+}
+
+/// Byte at `offset` of every benchmark file.
+fn pattern(offset: usize) -> u8 {
+    (offset % 251) as u8
+}
+
+fn fill(buf: &mut [u8], offset: usize) {
+    for (i, b) in buf.iter_mut().enumerate() {
+        *b = pattern(offset + i);
+    }
+}
+
+fn verify(buf: &[u8], offset: usize, file: &str) -> Result<(), KernelError> {
+    match buf
+        .iter()
+        .enumerate()
+        .find(|&(i, &b)| b != pattern(offset + i))
+    {
+        None => Ok(()),
+        Some(_) => {
+            crate::println!("[BENCH] {}: data mismatch at offset {}", file, offset);
+            Err(KernelError::FsError(FsError::CorruptedData))
+        }
+    }
+}
+
+/// xorshift64: reproducible offsets for the random tests
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Time `f`, returning its result and the elapsed nanoseconds.
+fn timed<R>(f: impl FnOnce() -> R) -> (R, u64) {
+    let start = read_timestamp();
+    let result = f();
+    (result, cycles_to_ns(read_timestamp().saturating_sub(start)))
+}
+
+/// Directory the tests run in, and how to flush it.
+struct Workspace {
+    dir: Arc<dyn VfsNode>,
+    /// Set for [`Target::Scratch`]
+    scratch: Option<BlockFs>,
+}
+
+impl Workspace {
+    fn open(target: &Target, config: &FsBenchConfig) -> Result<Self, KernelError> {
+        match target {
+            Target::Scratch => {
+                let data = config.file_size * 2 + config.metadata_files * BLOCK_SIZE;
+                let blocks = (data / BLOCK_SIZE + 1024) as u32;
+                let inodes = (config.metadata_files + config.readers + 64) as u32;
+                let fs = BlockFs::format(blocks, inodes)?;
+                let disk = RamBlockDevice::new(String::from("benchram"), 512, blocks as u64 * 8);
+                let backend = BlockDeviceBackend::new(Arc::new(Mutex::new(disk)))?;
+                fs.set_disk_backend(Arc::new(Mutex::new(backend)), false)?;
+                Ok(Self {
+                    dir: fs.root(),
+                    scratch: Some(fs),
+                })
+            }
+            Target::Directory(path) => Ok(Self {
+                dir: crate::fs::get_vfs().read().resolve_path(path)?,
+                scratch: None,
+            }),
+        }
+    }
+
+    fn sync(&self) -> Result<(), KernelError> {
+        match &self.scratch {
+            Some(fs) => fs.sync(),
+            None => crate::fs::get_vfs().read().sync(),
+        }
+    }
+
+    fn create(&self, name: &str) -> Result<Arc<dyn VfsNode>, KernelError> {
+        // Left over from an interrupted run
+        let _ = self.dir.unlink(name);
+        self.dir.create(name, Permissions::default())
+    }
+}
+
+/// Write `size` bytes of pattern to `file`, untimed.
+fn prefill(file: &dyn VfsNode, size: usize, io_size: usize) -> Result<(), KernelError> {
+    let mut buf = vec![0u8; io_size];
+    for offset in (0..size).step_by(io_size) {
+        let len = io_size.min(size - offset);
+        fill(&mut buf[..len], offset);
+        file.write(offset, &buf[..len])?;
+    }
+    Ok(())
+}
+
+fn sequential_write(ws: &Workspace, config: &FsBenchConfig) -> Result<FsBenchResult, KernelError> {
+    let file = ws.create("bench.seq")?;
+    let mut buf = vec![0u8; config.io_size];
+    let mut samples = Vec::with_capacity(config.file_size / config.io_size + 1);
+    let start = read_timestamp();
+    for offset in (0..config.file_size).step_by(config.io_size) {
+        let len = config.io_size.min(config.file_size - offset);
+        fill(&mut buf[..len], offset);
+        let (written, ns) = timed(|| file.write(offset, &buf[..len]));
+        written?;
+        samples.push(ns);
+    }
+    ws.sync()?;
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+    Ok(FsBenchResult::new(
+        "seq-write",
+        config.file_size as u64,
+        total,
+        &mut samples,
+    ))
+}
+
+fn sequential_read(ws: &Workspace, config: &FsBenchConfig) -> Result<FsBenchResult, KernelError> {
+    let file = ws.dir.lookup("bench.seq")?;
+    let mut buf = vec![0u8; config.io_size];
+    let mut samples = Vec::with_capacity(config.file_size / config.io_size + 1);
+    let start = read_timestamp();
+    for offset in (0..config.file_size).step_by(config.io_size) {
+        let len = config.io_size.min(config.file_size - offset);
+        let (read, ns) = timed(|| file.read(offset, &mut buf[..len]));
+        verify(&buf[..read?], offset, "bench.seq")?;
+        samples.push(ns);
+    }
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+    Ok(FsBenchResult::new(
+        "seq-read",
+        config.file_size as u64,
+        total,
+        &mut samples,
+    ))
+}
+
+/// Random `io_size`-aligned offsets within the sequential test's file.
+fn random_offsets(config: &FsBenchConfig, seed: u64) -> Vec<usize> {
+    let chunks = (config.file_size / config.io_size).max(1) as u64;
+    let mut rng = Rng(seed);
+    (0..config.random_ops)
+        .map(|_| (rng.next() % chunks) as usize * config.io_size)
+        .collect()
+}
+
+fn random_write(ws: &Workspace, config: &FsBenchConfig) -> Result<FsBenchResult, KernelError> {
+    let file = ws.dir.lookup("bench.seq")?;
+    let mut buf = vec![0u8; config.io_size];
+    let mut samples = Vec::with_capacity(config.random_ops);
+    let offsets = random_offsets(config, 0x9E37_79B9_7F4A_7C15);
+    let start = read_timestamp();
+    for &offset in &offsets {
+        // Same pattern as before, so later reads still verify
+        fill(&mut buf, offset);
+        let (written, ns) = timed(|| file.write(offset, &buf));
+        written?;
+        samples.push(ns);
+    }
+    ws.sync()?;
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+    Ok(FsBenchResult::new(
+        "rand-write",
+        (offsets.len() * config.io_size) as u64,
+        total,
+        &mut samples,
+    ))
+}
+
+fn random_read(ws: &Workspace, config: &FsBenchConfig) -> Result<FsBenchResult, KernelError> {
+    let file = ws.dir.lookup("bench.seq")?;
+    let mut buf = vec![0u8; config.io_size];
+    let mut samples = Vec::with_capacity(config.random_ops);
+    let offsets = random_offsets(config, 0xD1B5_4A32_D192_ED03);
+    let start = read_timestamp();
+    for &offset in &offsets {
+        let (read, ns) = timed(|| file.read(offset, &mut buf));
+        verify(&buf[..read?], offset, "bench.seq")?;
+        samples.push(ns);
+    }
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+    Ok(FsBenchResult::new(
+        "rand-read",
+        (offsets.len() * config.io_size) as u64,
+        total,
+        &mut samples,
+    ))
+}
+
+/// Create-and-write, then unlink, `metadata_files` small files. Returns
+/// the create and the unlink results.
+fn metadata(
+    ws: &Workspace,
+    config: &FsBenchConfig,
+) -> Result<(FsBenchResult, FsBenchResult), KernelError> {
+    let names: Vec<String> = (0..config.metadata_files)
+        .map(|i| format!("bench.meta.{}", i))
+        .collect();
+    let mut payload = [0u8; 128];
+    fill(&mut payload, 0);
+
+    let mut samples = Vec::with_capacity(names.len());
+    let start = read_timestamp();
+    for name in &names {
+        let (created, ns) = timed(|| ws.create(name)?.write(0, &payload));
+        created?;
+        samples.push(ns);
+    }
+    ws.sync()?;
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+    let create = FsBenchResult::new("create", 0, total, &mut samples);
+
+    samples.clear();
+    let start = read_timestamp();
+    for name in &names {
+        let (removed, ns) = timed(|| ws.dir.unlink(name));
+        removed?;
+        samples.push(ns);
+    }
+    ws.sync()?;
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+    Ok((create, FsBenchResult::new("unlink", 0, total, &mut samples)))
+}
+
+/// `readers` files of `file_size / readers` bytes each, read back a chunk
+/// per reader in turn.
+///
+/// The kernel has no worker threads to run the readers on, so they are
+/// interleaved rather than parallel: what this measures is how the cache
+/// copes with several sequential streams at once.
+fn concurrent_read(ws: &Workspace, config: &FsBenchConfig) -> Result<FsBenchResult, KernelError> {
+    let readers = config.readers.max(1);
+    let size = config.file_size / readers;
+    let mut files = Vec::with_capacity(readers);
+    for i in 0..readers {
+        let name = format!("bench.reader.{}", i);
+        let file = ws.create(&name)?;
+        prefill(file.as_ref(), size, config.io_size)?;
+        files.push((name, file));
+    }
+    ws.sync()?;
+
+    let mut buf = vec![0u8; config.io_size];
+    let mut samples = Vec::with_capacity(config.file_size / config.io_size + readers);
+    let start = read_timestamp();
+    for offset in (0..size).step_by(config.io_size) {
+        let len = config.io_size.min(size - offset);
+        for (name, file) in &files {
+            let (read, ns) = timed(|| file.read(offset, &mut buf[..len]));
+            verify(&buf[..read?], offset, name)?;
+            samples.push(ns);
+        }
+    }
+    let total = cycles_to_ns(read_timestamp().saturating_sub(start));
+
+    for (name, _) in &files {
+        ws.dir.unlink(name)?;
+    }
+    Ok(FsBenchResult::new(
+        "readers",
+        (size * readers) as u64,
+        total,
+        &mut samples,
+    ))
+}
+
+/// Run the whole suite on `target`.
+///
+/// Stops at the first failing operation or data mismatch; the benchmark
+/// files are removed either way.
+pub fn run(target: &Target, config: &FsBenchConfig) -> Result<Vec<FsBenchResult>, KernelError> {
+    if config.io_size == 0 || config.file_size < config.io_size {
+        return Err(KernelError::InvalidArgument {
+            name: "io_size",
+            value: "zero or larger than the file",
+        });
+    }
+    let ws = Workspace::open(target, config)?;
+
+    let run_all = || -> Result<Vec<FsBenchResult>, KernelError> {
+        let mut results = vec![
+            sequential_write(&ws, config)?,
+            sequential_read(&ws, config)?,
+            random_write(&ws, config)?,
+            random_read(&ws, config)?,
+        ];
+        let (create, unlink) = metadata(&ws, config)?;
+        results.extend([create, unlink, concurrent_read(&ws, config)?]);
+        Ok(results)
+    };
+    let results = run_all();
+
+    let _ = ws.dir.unlink("bench.seq");
+    if results.is_err() {
+        for i in 0..config.metadata_files {
+            let _ = ws.dir.unlink(&format!("bench.meta.{}", i));
+        }
+        for i in 0..config.readers {
+            let _ = ws.dir.unlink(&format!("bench.reader.{}", i));
+        }
+    }
+    let _ = ws.sync();
+    results
+}
+
+/// Print `results` as a table.
+pub fn print_results(results: &[FsBenchResult]) {
+    crate::println!(
+        "{:<11} {:>7} {:>10} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "test",
+        "ops",
+        "KiB/s",
+        "ops/s",
+        "p50 ns",
+        "p90 ns",
+        "p99 ns",
+        "max ns"
+    );
+    for r in results {
+        let kib = if r.bytes == 0 {
+            "-".to_string()
+        } else {
+            format!("{}", r.kib_per_sec())
+        };
+        crate::println!(
+            "{:<11} {:>7} {:>10} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            r.name,
+            r.ops,
+            kib,
+            r.ops_per_sec(),
+            r.latency.p50_ns,
+            r.latency.p90_ns,
+            r.latency.p99_ns,
+            r.latency.max_ns
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_roundtrip() {
+        let mut buf = [0u8; 600];
+        fill(&mut buf, 4096);
+        assert!(verify(&buf, 4096, "t").is_ok());
+        buf[300] ^= 1;
+        assert!(verify(&buf, 4096, "t").is_err());
+    }
+
+    #[test]
+    fn test_random_offsets_aligned_and_in_range() {
+        let config = FsBenchConfig {
+            file_size: 64 * 1024,
+            io_size: 4096,
+            random_ops: 100,
+            ..FsBenchConfig::default()
+        };
+        let offsets = random_offsets(&config, 1);
+        assert_eq!(offsets.len(), 100);
+        assert!(offsets
+            .iter()
+            .all(|&o| o % 4096 == 0 && o + 4096 <= config.file_size));
+        assert_eq!(offsets, random_offsets(&config, 1));
+    }
+
+    #[test]
+    fn test_throughput() {
+        let mut samples = [1, 2, 3];
+        let r = FsBenchResult::new("t", 2 * 1024 * 1024, 500_000_000, &mut samples);
+        assert_eq!(r.kib_per_sec(), 4096);
+        assert_eq!(r.ops_per_sec(), 6);
+        assert_eq!(per_second(5, 0), 0);
+    }
+}
@@ -0,0 +1,414 @@
+//! IPC latency and throughput benchmarks
+//!
+//! Times the IPC paths the kernel uses between endpoints with the test
+//! framework's [`BenchmarkRunner`] and reports latency percentiles for:
+//! - `rtt-small`: a small request and its reply between two endpoints
+//! - `shm-bulk`: a producer filling a chunk of a shared region and posting a
+//!   large message describing it, and a consumer copying the chunk out
+//! - `wakeup`: a message posted to an idle endpoint, the sender yielding, and
+//!   the message dequeued once the scheduler comes back, while a set of load
+//!   endpoints carries traffic between samples
+//!
+//! Every run emits one `BENCH {...}` JSON line per test so CI can scrape
+//! the serial log, and [`check`] compares the results against
+//! [`BASELINES`]. The IPC integration test fails on any regression, and the
+//! `bench ipc` shell command reports them.
+//!
+//! There are no kernel worker threads yet, so the `wakeup` load is queue
+//! traffic plus whatever else is runnable. Without a current task (early
+//! test binaries) the yield is skipped and the sample is just the enqueue
+//! and dequeue.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use super::LatencySummary;
+use crate::{
+    ipc::{
+        channel::Endpoint,
+        message::MemoryRegion,
+        shared_memory::{Permission, SharedRegion},
+        IpcError, Message,
+    },
+    mm::{FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
+    process::ProcessId,
+    test_framework::BenchmarkRunner,
+};
+
+/// Endpoints and regions are owned by the kernel.
+const KERNEL_PID: ProcessId = ProcessId(0);
+
+/// Opcodes of the benchmark messages
+const OP_REQUEST: u32 = 1;
+const OP_REPLY: u32 = 2;
+const OP_BULK: u32 = 3;
+const OP_LOAD: u32 = 4;
+
+/// Messages kept queued on every load endpoint
+const LOAD_DEPTH: usize = 16;
+
+/// Parameters of a benchmark run
+#[derive(Debug, Clone, Copy)]
+pub struct IpcBenchConfig {
+    /// Timed iterations per test
+    pub iterations: u64,
+    /// Untimed iterations before them
+    pub warmup: u64,
+    /// Bytes moved per `shm-bulk` transfer
+    pub bulk_size: usize,
+    /// Busy endpoints during the `wakeup` test
+    pub load_endpoints: usize,
+}
+
+impl Default for IpcBenchConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            warmup: 100,
+            bulk_size: 64 * 1024,
+            load_endpoints: 8,
+        }
+    }
+}
+
+/// Outcome of one test
+#[derive(Debug, Clone)]
+pub struct IpcBenchResult {
+    pub name: &'static str,
+    /// Payload bytes moved per iteration (0 for latency-only tests)
+    pub bytes_per_op: u64,
+    pub latency: LatencySummary,
+}
+
+impl IpcBenchResult {
+    /// Payload throughput in bytes per second (0 for latency-only tests).
+    pub fn bytes_per_sec(&self) -> u64 {
+        if self.latency.mean_ns == 0 {
+            return 0;
+        }
+        (self.bytes_per_op as u128 * 1_000_000_000 / self.latency.mean_ns as u128) as u64
+    }
+
+    /// Machine-readable form of the result: `BENCH` and a JSON object.
+    pub fn record(&self) -> String {
+        let l = &self.latency;
+        format!(
+            "BENCH {{\"suite\":\"ipc\",\"test\":\"{}\",\"samples\":{},\"min_ns\":{},\"p50_ns\":{},\
+             \"p90_ns\":{},\"p99_ns\":{},\"max_ns\":{},\"mean_ns\":{},\"bytes_per_sec\":{}}}",
+            self.name,
+            l.samples,
+            l.min_ns,
+            l.p50_ns,
+            l.p90_ns,
+            l.p99_ns,
+            l.max_ns,
+            l.mean_ns,
+            self.bytes_per_sec()
+        )
+    }
+}
+
+/// Limits a test must stay within; 0 leaves a limit unchecked
+#[derive(Debug, Clone, Copy)]
+pub struct Baseline {
+    pub name: &'static str,
+    pub max_p99_ns: u64,
+    pub min_bytes_per_sec: u64,
+}
+
+/// Regression limits, deliberately loose enough for QEMU under TCG.
+pub const BASELINES: &[Baseline] = &[
+    // Phase 1 target for small-message IPC is < 5 us.
+    Baseline {
+        name: "rtt-small",
+        max_p99_ns: 5_000,
+        min_bytes_per_sec: 0,
+    },
+    Baseline {
+        name: "shm-bulk",
+        max_p99_ns: 0,
+        min_bytes_per_sec: 64 * 1024 * 1024,
+    },
+    // A woken receiver must run again within one 10 ms time slice.
+    Baseline {
+        name: "wakeup",
+        max_p99_ns: 10_000_000,
+        min_bytes_per_sec: 0,
+    },
+];
+
+/// A result outside its baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regression {
+    pub name: &'static str,
+    pub metric: &'static str,
+    pub measured: u64,
+    pub limit: u64,
+}
+
+/// Compare `results` against `baselines`; tests without a baseline pass.
+pub fn check(results: &[IpcBenchResult], baselines: &[Baseline]) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for result in results {
+        let Some(base) = baselines.iter().find(|b| b.name == result.name) else {
+            continue;
+        };
+        if base.max_p99_ns != 0 && result.latency.p99_ns > base.max_p99_ns {
+            regressions.push(Regression {
+                name: result.name,
+                metric: "p99_ns",
+                measured: result.latency.p99_ns,
+                limit: base.max_p99_ns,
+            });
+        }
+        let throughput = result.bytes_per_sec();
+        if base.min_bytes_per_sec != 0 && throughput < base.min_bytes_per_sec {
+            regressions.push(Regression {
+                name: result.name,
+                metric: "bytes_per_sec",
+                measured: throughput,
+                limit: base.min_bytes_per_sec,
+            });
+        }
+    }
+    regressions
+}
+
+/// Run every test.
+pub fn run(config: &IpcBenchConfig) -> Result<Vec<IpcBenchResult>, IpcError> {
+    let runner = BenchmarkRunner::with_iterations(config.iterations, config.warmup);
+    Ok(vec![
+        small_round_trip(&runner)?,
+        shm_bulk(&runner, config.bulk_size)?,
+        wakeup(&runner, config.load_endpoints)?,
+    ])
+}
+
+/// Runs `f` on every iteration and remembers its first error, since the
+/// runner's closures cannot return one.
+struct Checked(Result<(), IpcError>);
+
+impl Checked {
+    fn run(&mut self, f: impl FnOnce() -> Result<(), IpcError>) {
+        if self.0.is_ok() {
+            self.0 = f();
+        }
+    }
+}
+
+fn small_round_trip(runner: &BenchmarkRunner) -> Result<IpcBenchResult, IpcError> {
+    let client = Endpoint::new(KERNEL_PID);
+    let server = Endpoint::new(KERNEL_PID);
+    let mut status = Checked(Ok(()));
+
+    let latency = runner.run_sampled(|| {
+        status.run(|| {
+            server.send_sync(Message::small(client.id(), OP_REQUEST), KERNEL_PID)?;
+            let request = server.receive_sync(KERNEL_PID)?;
+            client.send_sync(Message::small(request.capability(), OP_REPLY), KERNEL_PID)?;
+            client.receive_sync(KERNEL_PID).map(drop)
+        })
+    });
+    client.close();
+    server.close();
+
+    status.0.map(|()| IpcBenchResult {
+        name: "rtt-small",
+        bytes_per_op: 0,
+        latency,
+    })
+}
+
+fn shm_bulk(runner: &BenchmarkRunner, size: usize) -> Result<IpcBenchResult, IpcError> {
+    let region = SharedRegion::new(KERNEL_PID, size, Permission::READ_WRITE)?;
+    let base = crate::mm::phys_to_virt_addr(region.physical_base().as_u64()) as *mut u8;
+    let endpoint = Endpoint::new(KERNEL_PID);
+    let source: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+    let mut sink = vec![0u8; size];
+    let mut status = Checked(Ok(()));
+
+    let latency = runner.run_sampled(|| {
+        status.run(|| {
+            // SAFETY: `base` is the kernel mapping of the region's frames,
+            // which are at least `size` bytes and owned by this function
+            // until they are freed below.
+            unsafe { core::ptr::copy_nonoverlapping(source.as_ptr(), base, size) };
+            let chunk = MemoryRegion::new(0, size as u64);
+            endpoint.send_async(Message::large(region.id(), OP_BULK, chunk))?;
+
+            let Message::Large(msg) = endpoint.try_receive()? else {
+                return Err(IpcError::InvalidMessage);
+            };
+            let len = msg.memory_region.size as usize;
+            // SAFETY: as above; `len` is the `size` the producer posted.
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    base.add(msg.memory_region.base_addr as usize),
+                    sink.as_mut_ptr(),
+                    len,
+                )
+            };
+            Ok(())
+        })
+    });
+    endpoint.close();
+
+    let _ = FRAME_ALLOCATOR.lock().free_frames(
+        FrameNumber::new(region.physical_base().as_u64() / FRAME_SIZE as u64),
+        region.size() / FRAME_SIZE,
+    );
+
+    if status.0.is_ok() && sink != source {
+        crate::println!("[BENCH] shm-bulk: consumer saw corrupted data");
+        return Err(IpcError::InvalidMessage);
+    }
+    status.0.map(|()| IpcBenchResult {
+        name: "shm-bulk",
+        bytes_per_op: size as u64,
+        latency,
+    })
+}
+
+fn wakeup(runner: &BenchmarkRunner, load_endpoints: usize) -> Result<IpcBenchResult, IpcError> {
+    let load: Vec<Endpoint> = (0..load_endpoints)
+        .map(|_| Endpoint::new(KERNEL_PID))
+        .collect();
+    for endpoint in &load {
+        for _ in 0..LOAD_DEPTH {
+            endpoint.send_async(Message::small(0, OP_LOAD))?;
+        }
+    }
+    let target = Endpoint::new(KERNEL_PID);
+    let can_yield = crate::sched::SCHEDULER.lock().current().is_some();
+    let mut load_status = Checked(Ok(()));
+    let mut status = Checked(Ok(()));
+
+    let latency = runner.run_sampled_with(
+        || {
+            load_status.run(|| {
+                for endpoint in &load {
+                    endpoint.send_async(Message::small(0, OP_LOAD))?;
+                    endpoint.try_receive()?;
+                }
+                Ok(())
+            })
+        },
+        || {
+            status.run(|| {
+                target.send_async(Message::small(0, OP_REQUEST))?;
+                if can_yield {
+                    crate::sched::yield_cpu();
+                }
+                target.try_receive().map(drop)
+            })
+        },
+    );
+    target.close();
+    for endpoint in &load {
+        endpoint.close();
+    }
+
+    load_status.0.and(status.0).map(|()| IpcBenchResult {
+        name: "wakeup",
+        bytes_per_op: 0,
+        latency,
+    })
+}
+
+/// Print `results` as a table, each followed by its `BENCH` record.
+pub fn print_results(results: &[IpcBenchResult]) {
+    crate::println!(
+        "{:<10} {:>7} {:>10} {:>9} {:>9} {:>9} {:>9}",
+        "test",
+        "samples",
+        "MiB/s",
+        "p50 ns",
+        "p90 ns",
+        "p99 ns",
+        "max ns"
+    );
+    for r in results {
+        let mib = if r.bytes_per_op == 0 {
+            String::from("-")
+        } else {
+            format!("{}", r.bytes_per_sec() / (1024 * 1024))
+        };
+        crate::println!(
+            "{:<10} {:>7} {:>10} {:>9} {:>9} {:>9} {:>9}",
+            r.name,
+            r.latency.samples,
+            mib,
+            r.latency.p50_ns,
+            r.latency.p90_ns,
+            r.latency.p99_ns,
+            r.latency.max_ns
+        );
+    }
+    for r in results {
+        crate::println!("{}", r.record());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &'static str, bytes_per_op: u64, p99_ns: u64, mean_ns: u64) -> IpcBenchResult {
+        IpcBenchResult {
+            name,
+            bytes_per_op,
+            latency: LatencySummary {
+                samples: 10,
+                p99_ns,
+                mean_ns,
+                ..LatencySummary::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_bytes_per_sec() {
+        assert_eq!(
+            result("shm-bulk", 1024, 0, 1000).bytes_per_sec(),
+            1_024_000_000
+        );
+        assert_eq!(result("shm-bulk", 1024, 0, 0).bytes_per_sec(), 0);
+        assert_eq!(result("rtt-small", 0, 0, 500).bytes_per_sec(), 0);
+    }
+
+    #[test]
+    fn test_check_flags_only_regressions() {
+        let results = [
+            result("rtt-small", 0, 6_000, 1_000),
+            result("shm-bulk", 64 * 1024, 0, 1_000_000),
+            result("wakeup", 0, 1_000, 500),
+            result("unknown", 0, u64::MAX, 1),
+        ];
+        let regressions = check(&results, BASELINES);
+        assert_eq!(
+            regressions,
+            [
+                Regression {
+                    name: "rtt-small",
+                    metric: "p99_ns",
+                    measured: 6_000,
+                    limit: 5_000,
+                },
+                Regression {
+                    name: "shm-bulk",
+                    metric: "bytes_per_sec",
+                    measured: 65_536_000,
+                    limit: 64 * 1024 * 1024,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_is_one_json_line() {
+        let record = result("rtt-small", 0, 700, 400).record();
+        assert!(record.starts_with("BENCH {\"suite\":\"ipc\",\"test\":\"rtt-small\""));
+        assert!(record.contains("\"p99_ns\":700,"));
+        assert!(record.ends_with("\"bytes_per_sec\":0}"));
+        assert!(!record.contains('\n'));
+    }
+}
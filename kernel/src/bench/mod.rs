@@ -7,6 +7,11 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "alloc")]
+pub mod fs;
+#[cfg(feature = "alloc")]
+pub mod ipc;
+
 /// Trait for benchmarkable operations
 pub trait Benchmark {
     /// Run the benchmark and return the result
@@ -56,6 +61,42 @@ impl BenchmarkResult {
     }
 }
 
+/// Latency distribution of a set of timed operations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub min_ns: u64,
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+    pub mean_ns: u64,
+}
+
+impl LatencySummary {
+    /// Summarize `samples` (nanoseconds), sorting them in place.
+    ///
+    /// Percentiles use the nearest-rank method, so every reported value is
+    /// one that was actually measured.
+    pub fn from_samples(samples: &mut [u64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let n = samples.len();
+        let rank = |p: usize| samples[(n * p).div_ceil(100).max(1) - 1];
+        Self {
+            samples: n,
+            min_ns: samples[0],
+            p50_ns: rank(50),
+            p90_ns: rank(90),
+            p99_ns: rank(99),
+            max_ns: samples[n - 1],
+            mean_ns: samples.iter().sum::<u64>() / n as u64,
+        }
+    }
+}
+
 /// Architecture-specific timestamp counter.
 ///
 /// Re-exports the centralized [`crate::arch::entropy::read_timestamp`] which
@@ -201,3 +242,31 @@ pub fn black_box<T>(x: T) -> T {
         ret
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_summary_percentiles() {
+        let mut samples: [u64; 10] = [10, 1, 9, 2, 8, 3, 7, 4, 6, 5];
+        let summary = LatencySummary::from_samples(&mut samples);
+        assert_eq!(summary.samples, 10);
+        assert_eq!(summary.min_ns, 1);
+        assert_eq!(summary.p50_ns, 5);
+        assert_eq!(summary.p90_ns, 9);
+        assert_eq!(summary.p99_ns, 10);
+        assert_eq!(summary.max_ns, 10);
+        assert_eq!(summary.mean_ns, 5);
+    }
+
+    #[test]
+    fn test_latency_summary_empty_and_single() {
+        assert_eq!(
+            LatencySummary::from_samples(&mut []),
+            LatencySummary::default()
+        );
+        let summary = LatencySummary::from_samples(&mut [42]);
+        assert_eq!((summary.p50_ns, summary.p99_ns), (42, 42));
+    }
+}
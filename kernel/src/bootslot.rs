@@ -0,0 +1,896 @@
+//! A/B kernel boot slots with automatic rollback
+//!
+//! A boot disk built with `bootimage-builder --ab` is a GPT disk holding,
+//! next to the EFI system partition, two kernel slot partitions
+//! ([`KERNEL_PARTITIONS`]) and a small boot-selection partition
+//! ([`BOOTSEL_PARTITION`]). The boot-selection record names the active slot
+//! and, per slot, its [`SlotState`], the boot attempts it has left and the
+//! length and CRC-32 of the image it holds. Two copies of the record are kept
+//! in the first two sectors of the partition and written alternately, so a
+//! torn write always leaves the previous record readable.
+//!
+//! The stock UEFI loader always starts the kernel file on the ESP. The
+//! builder lays that file out contiguously and stores its extent in the
+//! record (the boot *window*); making a slot active copies its image into
+//! the window. That copy is the one step that is not crash-safe.
+//!
+//! Update flow:
+//!
+//! 1. `pkgd` stages a new kernel with [`install`] (`SYS_BOOT_SLOT_INSTALL`):
+//!    the image goes into the inactive slot, which is marked
+//!    [`SlotState::Pending`] with `bootslot.tries=` (default [`DEFAULT_TRIES`])
+//!    attempts, made active and copied into the window.
+//! 2. Every boot of a pending slot uses up one attempt ([`init`]).
+//! 3. Once user space is up, init calls [`commit`] (`SYS_BOOT_SLOT_COMMIT`) and
+//!    the slot becomes [`SlotState::Good`].
+//! 4. A boot that finds the active slot pending with no attempts left marks it
+//!    [`SlotState::Bad`], makes the other slot active again and resets the
+//!    machine.
+//!
+//! Attempts are counted by the new kernel itself, early in bootstrap; one
+//! that dies before [`init`] runs needs a manual reset into the old slot.
+//! Only the virtio-blk disk is probed.
+
+use alloc::vec;
+
+use spin::Mutex;
+
+use crate::{
+    drivers::virtio::blk::{self, BlockDevice, BLOCK_SIZE},
+    error::KernelError,
+};
+
+/// GPT partition names of kernel slots A and B.
+pub const KERNEL_PARTITIONS: [&str; SLOT_COUNT] = ["veridian-kernel-a", "veridian-kernel-b"];
+/// GPT partition name of the boot-selection record.
+pub const BOOTSEL_PARTITION: &str = "veridian-bootsel";
+/// GPT partition name of an optional root filesystem (TAR or BlockFS) on the
+/// same disk.
+pub const ROOTFS_PARTITION: &str = "veridian-rootfs";
+
+/// Number of kernel slots.
+pub const SLOT_COUNT: usize = 2;
+/// Boot attempts a newly installed kernel gets before it is rolled back.
+pub const DEFAULT_TRIES: u8 = 3;
+
+const RECORD_MAGIC: &[u8; 4] = b"VBSR";
+const RECORD_VERSION: u16 = 1;
+/// Encoded record length, including the trailing CRC.
+const RECORD_LEN: usize = 60;
+const SLOT_ENTRY_OFFSET: usize = 24;
+const SLOT_ENTRY_LEN: usize = 16;
+
+/// Sectors read and written at a time when copying images.
+const COPY_CHUNK_SECTORS: usize = 128;
+
+// ---------------------------------------------------------------------------
+// Boot-selection record
+// ---------------------------------------------------------------------------
+
+/// State of one kernel slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SlotState {
+    /// No image, or an install was interrupted.
+    Empty = 0,
+    /// Booted successfully and committed by user space.
+    Good = 1,
+    /// Installed but not yet committed; boots while attempts remain.
+    Pending = 2,
+    /// Ran out of attempts without being committed.
+    Bad = 3,
+}
+
+impl SlotState {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Empty),
+            1 => Some(Self::Good),
+            2 => Some(Self::Pending),
+            3 => Some(Self::Bad),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Good => "good",
+            Self::Pending => "pending",
+            Self::Bad => "bad",
+        }
+    }
+}
+
+/// Per-slot part of the boot-selection record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotInfo {
+    pub state: SlotState,
+    /// Boot attempts left while [`SlotState::Pending`].
+    pub tries_left: u8,
+    /// Length of the kernel image in bytes.
+    pub image_len: u32,
+    /// CRC-32 of the kernel image.
+    pub image_crc: u32,
+    /// Incremented on every install into the slot.
+    pub generation: u32,
+}
+
+impl SlotInfo {
+    const EMPTY: Self = Self {
+        state: SlotState::Empty,
+        tries_left: 0,
+        image_len: 0,
+        image_crc: 0,
+        generation: 0,
+    };
+}
+
+/// The boot-selection record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootRecord {
+    /// Write sequence number; the copy with the higher one is current.
+    pub seq: u32,
+    /// Index of the slot the loader should boot.
+    pub active: usize,
+    /// First disk sector of the ESP kernel file.
+    pub window_lba: u64,
+    /// Size of the ESP kernel file in sectors.
+    pub window_sectors: u32,
+    pub slots: [SlotInfo; SLOT_COUNT],
+}
+
+/// What [`BootRecord::begin_boot`] decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootAction {
+    /// The active slot is good.
+    Normal,
+    /// Trial boot of a pending slot; one attempt was used up.
+    Trial { tries_left: u8 },
+    /// The active slot failed; the record now points back at slot `to`.
+    Rollback { from: usize, to: usize },
+    /// The active slot failed but the other slot is not good either.
+    NoFallback,
+}
+
+impl BootRecord {
+    /// Parse a record sector. Returns `None` for a blank or corrupt copy.
+    pub fn decode(sector: &[u8]) -> Option<Self> {
+        if sector.len() < RECORD_LEN || &sector[0..4] != RECORD_MAGIC {
+            return None;
+        }
+        if read_u16(sector, 4) != RECORD_VERSION
+            || read_u32(sector, RECORD_LEN - 4) != crc32(&sector[..RECORD_LEN - 4])
+        {
+            return None;
+        }
+        let active = sector[6] as usize;
+        if active >= SLOT_COUNT {
+            return None;
+        }
+
+        let mut slots = [SlotInfo::EMPTY; SLOT_COUNT];
+        for (i, slot) in slots.iter_mut().enumerate() {
+            let e = &sector[SLOT_ENTRY_OFFSET + i * SLOT_ENTRY_LEN..];
+            *slot = SlotInfo {
+                state: SlotState::from_u8(e[0])?,
+                tries_left: e[1],
+                image_len: read_u32(e, 4),
+                image_crc: read_u32(e, 8),
+                generation: read_u32(e, 12),
+            };
+        }
+
+        Some(Self {
+            seq: read_u32(sector, 8),
+            active,
+            window_sectors: read_u32(sector, 12),
+            window_lba: read_u64(sector, 16),
+            slots,
+        })
+    }
+
+    /// Serialize into a sector buffer (at least [`BLOCK_SIZE`] bytes).
+    pub fn encode(&self, sector: &mut [u8]) {
+        sector[..BLOCK_SIZE].fill(0);
+        sector[0..4].copy_from_slice(RECORD_MAGIC);
+        sector[4..6].copy_from_slice(&RECORD_VERSION.to_le_bytes());
+        sector[6] = self.active as u8;
+        sector[8..12].copy_from_slice(&self.seq.to_le_bytes());
+        sector[12..16].copy_from_slice(&self.window_sectors.to_le_bytes());
+        sector[16..24].copy_from_slice(&self.window_lba.to_le_bytes());
+        for (i, slot) in self.slots.iter().enumerate() {
+            let e = &mut sector[SLOT_ENTRY_OFFSET + i * SLOT_ENTRY_LEN..];
+            e[0] = slot.state as u8;
+            e[1] = slot.tries_left;
+            e[4..8].copy_from_slice(&slot.image_len.to_le_bytes());
+            e[8..12].copy_from_slice(&slot.image_crc.to_le_bytes());
+            e[12..16].copy_from_slice(&slot.generation.to_le_bytes());
+        }
+        let crc = crc32(&sector[..RECORD_LEN - 4]);
+        sector[RECORD_LEN - 4..RECORD_LEN].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// The slot that is not active.
+    pub fn inactive(&self) -> usize {
+        1 - self.active
+    }
+
+    /// Account for a boot of the active slot.
+    pub fn begin_boot(&mut self) -> BootAction {
+        let active = self.active;
+        let slot = &mut self.slots[active];
+        match slot.state {
+            SlotState::Good => return BootAction::Normal,
+            SlotState::Pending if slot.tries_left > 0 => {
+                slot.tries_left -= 1;
+                return BootAction::Trial {
+                    tries_left: slot.tries_left,
+                };
+            }
+            SlotState::Pending => {
+                slot.state = SlotState::Bad;
+            }
+            SlotState::Empty | SlotState::Bad => {}
+        }
+
+        let other = self.inactive();
+        if self.slots[other].state != SlotState::Good {
+            return BootAction::NoFallback;
+        }
+        self.active = other;
+        BootAction::Rollback {
+            from: active,
+            to: other,
+        }
+    }
+
+    /// Mark the active slot good. Returns whether it was pending.
+    pub fn commit(&mut self) -> bool {
+        let slot = &mut self.slots[self.active];
+        if slot.state != SlotState::Pending {
+            return false;
+        }
+        slot.state = SlotState::Good;
+        slot.tries_left = 0;
+        true
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn read_u64(buf: &[u8], off: usize) -> u64 {
+    (read_u32(buf, off) as u64) | ((read_u32(buf, off + 4) as u64) << 32)
+}
+
+/// Incremental CRC-32 (IEEE 802.3, as used by GPT and zlib).
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// CRC-32 of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+// ---------------------------------------------------------------------------
+// Disk layout
+// ---------------------------------------------------------------------------
+
+/// A contiguous range of disk sectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub first_lba: u64,
+    pub sectors: u64,
+}
+
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+/// Partition name field: 36 UTF-16LE code units.
+const GPT_NAME_UNITS: usize = 36;
+
+/// Whether a GPT partition entry's name field equals `name` (ASCII).
+fn gpt_name_matches(field: &[u8], name: &str) -> bool {
+    let name = name.as_bytes();
+    if name.len() > GPT_NAME_UNITS {
+        return false;
+    }
+    (0..GPT_NAME_UNITS).all(|i| {
+        let unit = read_u16(field, i * 2);
+        let expect = name.get(i).copied().unwrap_or(0) as u16;
+        unit == expect
+    })
+}
+
+/// Find the partition called `name` in the GPT of `dev`.
+pub fn find_partition(dev: &mut dyn BlockDevice, name: &str) -> Option<Extent> {
+    let mut sector = [0u8; BLOCK_SIZE];
+    dev.read_block(1, &mut sector).ok()?;
+    if &sector[0..8] != GPT_SIGNATURE {
+        return None;
+    }
+    let entries_lba = read_u64(&sector, 72);
+    let entry_count = read_u32(&sector, 80) as u64;
+    let entry_size = read_u32(&sector, 84) as usize;
+    if !(128..=BLOCK_SIZE).contains(&entry_size) || !BLOCK_SIZE.is_multiple_of(entry_size) {
+        return None;
+    }
+    let per_sector = (BLOCK_SIZE / entry_size) as u64;
+
+    let mut cached = u64::MAX;
+    for index in 0..entry_count.min(256) {
+        let lba = entries_lba + index / per_sector;
+        if lba != cached {
+            dev.read_block(lba, &mut sector).ok()?;
+            cached = lba;
+        }
+        let off = (index % per_sector) as usize * entry_size;
+        let entry = &sector[off..off + entry_size];
+        if entry[0..16].iter().all(|&b| b == 0) {
+            continue;
+        }
+        if gpt_name_matches(&entry[56..128], name) {
+            let first = read_u64(entry, 32);
+            let last = read_u64(entry, 40);
+            if last < first {
+                return None;
+            }
+            return Some(Extent {
+                first_lba: first,
+                sectors: last - first + 1,
+            });
+        }
+    }
+    None
+}
+
+const NOT_AB_DISK: KernelError = KernelError::NotFound {
+    resource: "A/B boot disk",
+    id: 0,
+};
+
+/// An A/B boot disk: where the slots live and the current record.
+#[derive(Debug, Clone)]
+pub struct AbDisk {
+    pub kernel: [Extent; SLOT_COUNT],
+    pub bootsel: Extent,
+    pub rootfs: Option<Extent>,
+    pub record: BootRecord,
+}
+
+impl AbDisk {
+    /// Recognize an A/B disk and read its record.
+    pub fn probe(dev: &mut dyn BlockDevice) -> Result<Self, KernelError> {
+        let bootsel = find_partition(dev, BOOTSEL_PARTITION).ok_or(NOT_AB_DISK)?;
+        let kernel = [
+            find_partition(dev, KERNEL_PARTITIONS[0]).ok_or(NOT_AB_DISK)?,
+            find_partition(dev, KERNEL_PARTITIONS[1]).ok_or(NOT_AB_DISK)?,
+        ];
+        if bootsel.sectors < 2 {
+            return Err(KernelError::InvalidArgument {
+                name: "boot-selection partition",
+                value: "smaller than two sectors",
+            });
+        }
+        let record = load_record(dev, bootsel)?;
+        Ok(Self {
+            kernel,
+            bootsel,
+            rootfs: find_partition(dev, ROOTFS_PARTITION),
+            record,
+        })
+    }
+
+    /// Write the record into the older of the two copies.
+    pub fn save(&mut self, dev: &mut dyn BlockDevice) -> Result<(), KernelError> {
+        self.record.seq = self.record.seq.wrapping_add(1);
+        let mut sector = [0u8; BLOCK_SIZE];
+        self.record.encode(&mut sector);
+        let copy = (self.record.seq % 2) as u64;
+        dev.write_block(self.bootsel.first_lba + copy, &sector)
+    }
+
+    /// Copy the image of `slot` into the ESP kernel window, zero-filling the
+    /// rest of the window.
+    pub fn activate(&self, dev: &mut dyn BlockDevice, slot: usize) -> Result<(), KernelError> {
+        let info = &self.record.slots[slot];
+        let image_sectors = (info.image_len as u64).div_ceil(BLOCK_SIZE as u64);
+        let window_sectors = self.record.window_sectors as u64;
+        if self.record.window_lba == 0 || image_sectors > window_sectors {
+            return Err(KernelError::InvalidArgument {
+                name: "kernel image",
+                value: "does not fit the boot window",
+            });
+        }
+
+        let mut buf = vec![0u8; COPY_CHUNK_SECTORS * BLOCK_SIZE];
+        let mut done = 0u64;
+        while done < window_sectors {
+            let count = (window_sectors - done).min(COPY_CHUNK_SECTORS as u64) as usize;
+            let chunk = &mut buf[..count * BLOCK_SIZE];
+            chunk.fill(0);
+            for i in 0..count as u64 {
+                if done + i < image_sectors {
+                    let off = i as usize * BLOCK_SIZE;
+                    dev.read_block(
+                        self.kernel[slot].first_lba + done + i,
+                        &mut chunk[off..off + BLOCK_SIZE],
+                    )?;
+                }
+            }
+            for i in 0..count as u64 {
+                let off = i as usize * BLOCK_SIZE;
+                dev.write_block(
+                    self.record.window_lba + done + i,
+                    &chunk[off..off + BLOCK_SIZE],
+                )?;
+            }
+            done += count as u64;
+        }
+        Ok(())
+    }
+
+    /// Write `image` into the inactive slot and make it the pending active
+    /// slot with `tries` boot attempts. Returns the slot index.
+    ///
+    /// Refused while the active slot is still pending: the inactive slot is
+    /// then the only known-good kernel.
+    pub fn install(
+        &mut self,
+        dev: &mut dyn BlockDevice,
+        image: &[u8],
+        tries: u8,
+    ) -> Result<usize, KernelError> {
+        if self.record.slots[self.record.active].state == SlotState::Pending {
+            return Err(KernelError::InvalidState {
+                expected: "committed active slot",
+                actual: "pending",
+            });
+        }
+        let target = self.record.inactive();
+        let capacity = self.kernel[target]
+            .sectors
+            .min(self.record.window_sectors as u64)
+            * BLOCK_SIZE as u64;
+        if image.is_empty() || image.len() as u64 > capacity || image.len() > u32::MAX as usize {
+            return Err(KernelError::InvalidArgument {
+                name: "kernel image",
+                value: "empty or larger than the slot",
+            });
+        }
+
+        let generation = self.record.slots[target].generation.wrapping_add(1);
+        self.record.slots[target] = SlotInfo {
+            generation,
+            ..SlotInfo::EMPTY
+        };
+        self.save(dev)?;
+
+        let mut sector = [0u8; BLOCK_SIZE];
+        for (i, chunk) in image.chunks(BLOCK_SIZE).enumerate() {
+            sector.fill(0);
+            sector[..chunk.len()].copy_from_slice(chunk);
+            dev.write_block(self.kernel[target].first_lba + i as u64, &sector)?;
+        }
+        let image_crc = crc32(image);
+        if slot_crc(dev, self.kernel[target], image.len())? != image_crc {
+            return Err(KernelError::HardwareError {
+                device: "boot slot",
+                code: target as u32,
+            });
+        }
+
+        self.record.slots[target] = SlotInfo {
+            state: SlotState::Pending,
+            tries_left: tries.max(1),
+            image_len: image.len() as u32,
+            image_crc,
+            generation,
+        };
+        self.record.active = target;
+        self.save(dev)?;
+        self.activate(dev, target)?;
+        Ok(target)
+    }
+}
+
+/// Read both record copies and return the newest valid one.
+fn load_record(dev: &mut dyn BlockDevice, bootsel: Extent) -> Result<BootRecord, KernelError> {
+    let mut best: Option<BootRecord> = None;
+    let mut sector = [0u8; BLOCK_SIZE];
+    for copy in 0..2 {
+        dev.read_block(bootsel.first_lba + copy, &mut sector)?;
+        if let Some(record) = BootRecord::decode(&sector) {
+            let newer = match &best {
+                // Sequence numbers wrap; compare by distance.
+                Some(b) => (record.seq.wrapping_sub(b.seq) as i32) > 0,
+                None => true,
+            };
+            if newer {
+                best = Some(record);
+            }
+        }
+    }
+    best.ok_or(KernelError::InvalidState {
+        expected: "valid boot-selection record",
+        actual: "both copies corrupt",
+    })
+}
+
+/// CRC-32 of the first `len` bytes of a slot.
+fn slot_crc(dev: &mut dyn BlockDevice, slot: Extent, len: usize) -> Result<u32, KernelError> {
+    let mut sector = [0u8; BLOCK_SIZE];
+    let mut crc = 0;
+    let mut left = len;
+    let mut lba = slot.first_lba;
+    while left > 0 {
+        dev.read_block(lba, &mut sector)?;
+        let n = left.min(BLOCK_SIZE);
+        crc = crc32_update(crc, &sector[..n]);
+        left -= n;
+        lba += 1;
+    }
+    Ok(crc)
+}
+
+// ---------------------------------------------------------------------------
+// Boot-time state and system call entry points
+// ---------------------------------------------------------------------------
+
+static AB_DISK: Mutex<Option<AbDisk>> = Mutex::new(None);
+
+fn with_device<R>(
+    f: impl FnOnce(&mut dyn BlockDevice) -> Result<R, KernelError>,
+) -> Result<R, KernelError> {
+    let device = blk::get_device().ok_or(KernelError::NotInitialized {
+        subsystem: "virtio-blk",
+    })?;
+    let mut dev = device.lock();
+    f(&mut *dev)
+}
+
+/// Number of boot attempts for new installs (`bootslot.tries=`).
+fn configured_tries() -> u8 {
+    crate::utils::cmdline::param("bootslot.tries")
+        .and_then(|v| v.parse::<u8>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_TRIES)
+}
+
+/// Reset the machine so the loader picks up the new window contents.
+fn reset_machine() {
+    // Nothing is in flight this early in bootstrap, so skip the orderly
+    // reboot path.
+    crate::power::system::reset();
+    crate::println!("[BOOTSLOT] Reset failed; power-cycle to finish the rollback");
+}
+
+/// Probe the virtio-blk disk for A/B slots and account for this boot.
+///
+/// Called from bootstrap right after the virtio-blk driver is up. Does not
+/// return if the active slot has to be rolled back.
+pub fn init() {
+    let result = with_device(|dev| {
+        let mut disk = AbDisk::probe(dev)?;
+        let action = disk.record.begin_boot();
+        match action {
+            BootAction::Normal => {}
+            BootAction::Trial { tries_left } => {
+                crate::println!(
+                    "[BOOTSLOT] Trial boot of slot {} ({} attempts left after this one)",
+                    slot_name(disk.record.active),
+                    tries_left
+                );
+                disk.save(dev)?;
+            }
+            BootAction::Rollback { from, to } => {
+                crate::println!(
+                    "[BOOTSLOT] Slot {} was never committed, rolling back to slot {}",
+                    slot_name(from),
+                    slot_name(to)
+                );
+                disk.save(dev)?;
+                disk.activate(dev, to)?;
+            }
+            BootAction::NoFallback => {
+                crate::println!(
+                    "[BOOTSLOT] Slot {} is {} and there is no good slot to fall back to",
+                    slot_name(disk.record.active),
+                    disk.record.slots[disk.record.active].state.name()
+                );
+            }
+        }
+        Ok((disk, action))
+    });
+
+    match result {
+        Ok((disk, action)) => {
+            crate::println!(
+                "[BOOTSLOT] A/B disk: booted slot {} (generation {})",
+                slot_name(disk.record.active),
+                disk.record.slots[disk.record.active].generation
+            );
+            *AB_DISK.lock() = Some(disk);
+            if let BootAction::Rollback { .. } = action {
+                reset_machine();
+            }
+        }
+        Err(KernelError::NotFound { .. }) | Err(KernelError::NotInitialized { .. }) => {}
+        Err(_e) => crate::println!("[BOOTSLOT] Ignoring A/B disk: {:?}", _e),
+    }
+}
+
+/// Whether the virtio-blk disk is an A/B boot disk.
+pub fn is_active() -> bool {
+    AB_DISK.lock().is_some()
+}
+
+/// The TAR root filesystem partition of the A/B disk, if it has one.
+pub fn rootfs_partition() -> Option<Extent> {
+    AB_DISK.lock().as_ref().and_then(|disk| disk.rootfs)
+}
+
+/// A copy of the current record.
+pub fn record() -> Option<BootRecord> {
+    AB_DISK.lock().as_ref().map(|disk| disk.record.clone())
+}
+
+/// User space reached a working state: commit the active slot. Returns
+/// whether a pending slot was committed.
+pub fn commit() -> Result<bool, KernelError> {
+    let mut guard = AB_DISK.lock();
+    let disk = guard.as_mut().ok_or(NOT_AB_DISK)?;
+    if !disk.record.commit() {
+        return Ok(false);
+    }
+    with_device(|dev| disk.save(dev))?;
+    crate::println!(
+        "[BOOTSLOT] Slot {} committed",
+        slot_name(disk.record.active)
+    );
+    Ok(true)
+}
+
+/// Stage a new kernel image in the inactive slot; it is booted on the next
+/// reset. Returns the slot index.
+pub fn install(image: &[u8]) -> Result<usize, KernelError> {
+    let mut guard = AB_DISK.lock();
+    let disk = guard.as_mut().ok_or(NOT_AB_DISK)?;
+    let tries = configured_tries();
+    let slot = with_device(|dev| disk.install(dev, image, tries))?;
+    crate::println!(
+        "[BOOTSLOT] Installed {} byte kernel into slot {} ({} boot attempts)",
+        image.len(),
+        slot_name(slot),
+        tries
+    );
+    Ok(slot)
+}
+
+/// "A" or "B".
+pub fn slot_name(slot: usize) -> &'static str {
+    if slot == 0 {
+        "A"
+    } else {
+        "B"
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    struct RamDisk(Vec<u8>);
+
+    impl BlockDevice for RamDisk {
+        fn read_block(&mut self, block_num: u64, buf: &mut [u8]) -> Result<(), KernelError> {
+            let off = block_num as usize * BLOCK_SIZE;
+            buf[..BLOCK_SIZE].copy_from_slice(&self.0[off..off + BLOCK_SIZE]);
+            Ok(())
+        }
+
+        fn write_block(&mut self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+            let off = block_num as usize * BLOCK_SIZE;
+            self.0[off..off + BLOCK_SIZE].copy_from_slice(&data[..BLOCK_SIZE]);
+            Ok(())
+        }
+
+        fn capacity_sectors(&self) -> u64 {
+            (self.0.len() / BLOCK_SIZE) as u64
+        }
+
+        fn is_read_only(&self) -> bool {
+            false
+        }
+    }
+
+    fn good(generation: u32) -> SlotInfo {
+        SlotInfo {
+            state: SlotState::Good,
+            tries_left: 0,
+            image_len: 1000,
+            image_crc: 0x1234_5678,
+            generation,
+        }
+    }
+
+    fn record() -> BootRecord {
+        BootRecord {
+            seq: 1,
+            active: 0,
+            window_lba: 100,
+            window_sectors: 8,
+            slots: [good(1), good(1)],
+        }
+    }
+
+    /// GPT with one entry per sector-sized slot: kernel A at 10, kernel B at
+    /// 20 (8 sectors each), bootsel at 30 (2 sectors); window at 40.
+    fn disk() -> RamDisk {
+        let mut img = vec![0u8; 64 * BLOCK_SIZE];
+        let hdr = BLOCK_SIZE;
+        img[hdr..hdr + 8].copy_from_slice(GPT_SIGNATURE);
+        img[hdr + 72..hdr + 80].copy_from_slice(&2u64.to_le_bytes());
+        img[hdr + 80..hdr + 84].copy_from_slice(&4u32.to_le_bytes());
+        img[hdr + 84..hdr + 88].copy_from_slice(&128u32.to_le_bytes());
+        let parts = [
+            ("esp", 40u64, 47u64),
+            (KERNEL_PARTITIONS[0], 10, 17),
+            (KERNEL_PARTITIONS[1], 20, 27),
+            (BOOTSEL_PARTITION, 30, 31),
+        ];
+        for (i, (name, first, last)) in parts.iter().enumerate() {
+            let e = 2 * BLOCK_SIZE + i * 128;
+            img[e] = 1;
+            img[e + 32..e + 40].copy_from_slice(&first.to_le_bytes());
+            img[e + 40..e + 48].copy_from_slice(&last.to_le_bytes());
+            for (j, b) in name.bytes().enumerate() {
+                img[e + 56 + j * 2] = b;
+            }
+        }
+        let mut rec = record();
+        rec.window_lba = 40;
+        let mut sector = [0u8; BLOCK_SIZE];
+        rec.encode(&mut sector);
+        // seq 1 lives in the second copy
+        img[31 * BLOCK_SIZE..32 * BLOCK_SIZE].copy_from_slice(&sector);
+        RamDisk(img)
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let mut rec = record();
+        rec.active = 1;
+        rec.slots[1] = SlotInfo {
+            state: SlotState::Pending,
+            tries_left: 2,
+            ..good(7)
+        };
+        let mut sector = [0u8; BLOCK_SIZE];
+        rec.encode(&mut sector);
+        assert_eq!(BootRecord::decode(&sector), Some(rec));
+
+        sector[30] ^= 1;
+        assert_eq!(BootRecord::decode(&sector), None);
+        assert_eq!(BootRecord::decode(&[0u8; BLOCK_SIZE]), None);
+    }
+
+    #[test]
+    fn test_trial_then_rollback() {
+        let mut rec = record();
+        rec.active = 1;
+        rec.slots[1].state = SlotState::Pending;
+        rec.slots[1].tries_left = 2;
+
+        assert_eq!(rec.begin_boot(), BootAction::Trial { tries_left: 1 });
+        assert_eq!(rec.begin_boot(), BootAction::Trial { tries_left: 0 });
+        assert_eq!(rec.begin_boot(), BootAction::Rollback { from: 1, to: 0 });
+        assert_eq!(rec.active, 0);
+        assert_eq!(rec.slots[1].state, SlotState::Bad);
+        assert_eq!(rec.begin_boot(), BootAction::Normal);
+    }
+
+    #[test]
+    fn test_commit_stops_countdown() {
+        let mut rec = record();
+        rec.slots[0].state = SlotState::Pending;
+        rec.slots[0].tries_left = 1;
+
+        assert_eq!(rec.begin_boot(), BootAction::Trial { tries_left: 0 });
+        assert!(rec.commit());
+        assert!(!rec.commit());
+        assert_eq!(rec.begin_boot(), BootAction::Normal);
+    }
+
+    #[test]
+    fn test_no_fallback() {
+        let mut rec = record();
+        rec.slots[0].state = SlotState::Pending;
+        rec.slots[1].state = SlotState::Empty;
+        assert_eq!(rec.begin_boot(), BootAction::NoFallback);
+        assert_eq!(rec.active, 0);
+    }
+
+    #[test]
+    fn test_newest_record_copy_wins() {
+        let mut dev = disk();
+        let mut ab = AbDisk::probe(&mut dev).unwrap();
+        assert_eq!(
+            ab.kernel[1],
+            Extent {
+                first_lba: 20,
+                sectors: 8
+            }
+        );
+        assert_eq!(
+            ab.bootsel,
+            Extent {
+                first_lba: 30,
+                sectors: 2
+            }
+        );
+        assert_eq!(ab.rootfs, None);
+
+        ab.record.active = 1;
+        ab.save(&mut dev).unwrap();
+        let rec = AbDisk::probe(&mut dev).unwrap().record;
+        assert_eq!((rec.seq, rec.active), (2, 1));
+
+        // A torn write leaves the previous copy in charge
+        dev.0[30 * BLOCK_SIZE + 20] ^= 0xFF;
+        let rec = AbDisk::probe(&mut dev).unwrap().record;
+        assert_eq!((rec.seq, rec.active), (1, 0));
+    }
+
+    #[test]
+    fn test_install_and_activate() {
+        let mut dev = disk();
+        let mut ab = AbDisk::probe(&mut dev).unwrap();
+        let image: Vec<u8> = (0..1500u32).map(|i| i as u8).collect();
+
+        assert_eq!(ab.install(&mut dev, &image, 3), Ok(1));
+        let rec = AbDisk::probe(&mut dev).unwrap().record;
+        assert_eq!(rec.active, 1);
+        assert_eq!(rec.slots[1].state, SlotState::Pending);
+        assert_eq!(rec.slots[1].tries_left, 3);
+        assert_eq!(rec.slots[1].image_crc, crc32(&image));
+        assert_eq!(rec.slots[1].generation, 2);
+
+        // The window holds the image followed by zeroes
+        let window = &dev.0[40 * BLOCK_SIZE..48 * BLOCK_SIZE];
+        assert_eq!(&window[..image.len()], &image[..]);
+        assert!(window[image.len()..].iter().all(|&b| b == 0));
+
+        // A second install before the first is committed is refused
+        assert!(ab.install(&mut dev, &image, 3).is_err());
+
+        let too_big = vec![1u8; 9 * BLOCK_SIZE];
+        ab.record.commit();
+        assert!(ab.install(&mut dev, &too_big, 3).is_err());
+    }
+}
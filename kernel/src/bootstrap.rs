@@ -3,11 +3,17 @@
 //! This module handles the multi-stage initialization process to avoid
 //! circular dependencies between subsystems.
 
+#[cfg(feature = "desktop")]
+use crate::desktop;
+#[cfg(feature = "net")]
+use crate::net;
+#[cfg(feature = "pkg")]
+use crate::pkg;
 #[cfg(target_arch = "x86_64")]
 use crate::virt;
 use crate::{
-    arch, audio, cap, desktop, error::KernelResult, fs, graphics, ipc, irq, mm, net, perf, pkg,
-    process, sched, security, services, timer, video,
+    arch, audio, cap, error::KernelResult, fs, graphics, ipc, irq, klog, mm, perf, process, sched,
+    security, services, timer, video,
 };
 
 #[cfg(feature = "alloc")]
@@ -117,8 +123,10 @@ fn switch_to_heap_stack(size: usize) {
     // Align to 16 bytes (x86_64 ABI requirement)
     let stack_top_aligned = stack_top & !0xF;
 
-    kprintln!(
-        "[BOOTSTRAP] Switching to heap stack ({} KB at {:#x})",
+    klog!(
+        Info,
+        "bootstrap",
+        "Switching to heap stack ({} KB at {:#x})",
         size / 1024,
         stack_top_aligned
     );
@@ -146,7 +154,7 @@ fn switch_to_heap_stack(size: usize) {
 #[cfg(target_arch = "x86_64")]
 extern "C" fn kernel_init_stage3_onwards() -> ! {
     if let Err(e) = kernel_init_stage3_impl() {
-        crate::println!("[BOOTSTRAP] FATAL: Stage 3+ init failed: {:?}", e);
+        klog!(Error, "bootstrap", "FATAL: Stage 3+ init failed: {:?}", e);
         loop {
             // SAFETY: Halting the CPU in an unrecoverable error loop. No
             // memory or stack side effects.
@@ -157,12 +165,12 @@ extern "C" fn kernel_init_stage3_onwards() -> ! {
     }
 
     // Stage 6: User space transition (same as run())
-    kprintln!("[BOOTSTRAP] Stage 6: User space transition");
-    kprintln!("[BOOTSTRAP] About to create init process...");
+    klog!(Info, "bootstrap", "Stage 6: User space transition");
+    klog!(Info, "bootstrap", "About to create init process...");
     create_init_process();
-    kprintln!("[BOOTSTRAP] Init process created");
-    kprintln!("[BOOTSTRAP] User space transition prepared");
-    kprintln!("[KERNEL] Boot sequence complete!");
+    klog!(Info, "bootstrap", "Init process created");
+    klog!(Info, "bootstrap", "User space transition prepared");
+    klog!(Info, "kernel", "Boot sequence complete!");
     kprintln!("BOOTOK");
 
     // User-mode entry via iretq is available but transitions to Ring 3
@@ -170,8 +178,16 @@ extern "C" fn kernel_init_stage3_onwards() -> ! {
     // primary interface, we skip the Ring 3 transition and go directly
     // to the shell. The Ring 3 pathway (SYSCALL/SYSRET) is verified
     // working in previous releases (v0.3.9+).
-    kprintln!("[BOOTSTRAP] User-mode entry available (Ring 3 via iretq)");
-    kprintln!("[BOOTSTRAP] Skipping Ring 3 transition for interactive shell");
+    klog!(
+        Info,
+        "bootstrap",
+        "User-mode entry available (Ring 3 via iretq)"
+    );
+    klog!(
+        Info,
+        "bootstrap",
+        "Skipping Ring 3 transition for interactive shell"
+    );
 
     // x86_64: Enable keyboard IRQ and CPU interrupts before launching the
     // shell. The keyboard driver was initialized in Stage 4; here we unmask
@@ -181,7 +197,7 @@ extern "C" fn kernel_init_stage3_onwards() -> ! {
         arch::x86_64::enable_keyboard_irq();
         arch::x86_64::enable_timer_irq();
         arch::x86_64::enable_interrupts();
-        kprintln!("[BOOTSTRAP] Keyboard IRQ + interrupts enabled");
+        klog!(Info, "bootstrap", "Keyboard IRQ + interrupts enabled");
     }
 
     // Enable framebuffer console output now that boot is complete.
@@ -189,6 +205,9 @@ extern "C" fn kernel_init_stage3_onwards() -> ! {
     // to a 1280x800 framebuffer is too slow in QEMU's emulated CPU).
     graphics::fbcon::enable_output();
 
+    // Boot is done; from here a hung scheduler resets the machine.
+    crate::drivers::watchdog::start_hardware();
+
     // Boot directly to the native vsh (kernel-space shell).
     // User-space shells (BusyBox ash, /bin/sh) can be launched from vsh
     // via the `ash` or `/bin/sh` command if a rootfs with BusyBox is loaded.
@@ -198,7 +217,11 @@ extern "C" fn kernel_init_stage3_onwards() -> ! {
         let has_sh = vfs.resolve_path("/bin/sh").is_ok();
         drop(vfs);
         if has_sh {
-            kprintln!("[BOOTSTRAP] BusyBox ash available at /bin/sh (run 'ash' from vsh)");
+            klog!(
+                Info,
+                "bootstrap",
+                "BusyBox ash available at /bin/sh (run 'ash' from vsh)"
+            );
         }
     }
 
@@ -206,7 +229,7 @@ extern "C" fn kernel_init_stage3_onwards() -> ! {
     // The shell provides a serial console REPL for all 3 architectures.
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Starting interactive shell...");
+        klog!(Info, "bootstrap", "Starting interactive shell...");
         crate::services::shell::run_shell();
     }
 
@@ -238,8 +261,16 @@ pub fn kernel_init() -> KernelResult<()> {
     }
 
     // Stage 1: Hardware initialization
-    kprintln!("[BOOTSTRAP] Starting multi-stage kernel initialization...");
-    kprintln!("[BOOTSTRAP] Stage 1: Hardware initialization");
+    klog!(
+        Info,
+        "bootstrap",
+        "Starting multi-stage kernel initialization..."
+    );
+    klog!(Info, "bootstrap", "Stage 1: Hardware initialization");
+
+    // Point the per-CPU base register at the boot CPU's area before anything
+    // that might look up per-CPU data.
+    crate::sched::percpu::init_cpu(0);
 
     arch::init();
 
@@ -248,14 +279,14 @@ pub fn kernel_init() -> KernelResult<()> {
     #[cfg(target_arch = "x86_64")]
     {
         crate::arch::x86_64::pat::init();
-        kprintln!("[BOOTSTRAP] PAT configured (WC available)");
+        klog!(Info, "bootstrap", "PAT configured (WC available)");
         crate::arch::x86_64::rtc::init();
     }
 
-    kprintln!("[BOOTSTRAP] Architecture initialized");
+    klog!(Info, "bootstrap", "Architecture initialized");
 
     // Stage 2: Memory management
-    kprintln!("[BOOTSTRAP] Stage 2: Memory management");
+    klog!(Info, "bootstrap", "Stage 2: Memory management");
 
     mm::init_default();
 
@@ -264,7 +295,10 @@ pub fn kernel_init() -> KernelResult<()> {
     #[cfg(target_arch = "x86_64")]
     mm::reserve_boot_page_table_frames();
 
-    kprintln!("[BOOTSTRAP] Memory management initialized");
+    klog!(Info, "bootstrap", "Memory management initialized");
+
+    // Console log level from the command line (needs the heap)
+    crate::log_service::log_init();
 
     // Verify heap allocation works (AArch64 requires -Zub-checks=no)
     #[cfg(target_arch = "aarch64")]
@@ -272,7 +306,7 @@ pub fn kernel_init() -> KernelResult<()> {
         let test_box = alloc::boxed::Box::new(42u64);
         assert!(*test_box == 42);
         drop(test_box);
-        kprintln!("[BOOTSTRAP] Heap allocation verified OK");
+        klog!(Info, "bootstrap", "Heap allocation verified OK");
     }
 
     // x86_64: Initialize framebuffer console (fbcon) so that all subsequent
@@ -298,7 +332,7 @@ pub fn kernel_init() -> KernelResult<()> {
                     format,
                 );
             }
-            kprintln!("[BOOTSTRAP] Framebuffer console initialized");
+            klog!(Info, "bootstrap", "Framebuffer console initialized");
 
             // Store the framebuffer physical address for user-space mmap.
             // The virtual address is fb_info.buffer; subtract PHYS_MEM_OFFSET to get
@@ -308,7 +342,7 @@ pub fn kernel_init() -> KernelResult<()> {
             if phys_offset > 0 {
                 let fb_phys = (fb_info.buffer as u64).wrapping_sub(phys_offset);
                 crate::graphics::framebuffer::set_phys_addr(fb_phys);
-                kprintln!("[BOOTSTRAP] Framebuffer phys addr: 0x{:x}", fb_phys);
+                klog!(Info, "bootstrap", "Framebuffer phys addr: 0x{:x}", fb_phys);
             }
 
             // Apply write-combining to the framebuffer's MMIO pages for
@@ -324,8 +358,10 @@ pub fn kernel_init() -> KernelResult<()> {
                     fb_size_aligned,
                 );
             }
-            kprintln!(
-                "[BOOTSTRAP] Framebuffer WC enabled ({} pages)",
+            klog!(
+                Info,
+                "bootstrap",
+                "Framebuffer WC enabled ({} pages)",
                 fb_size_aligned / 4096
             );
         }
@@ -350,10 +386,10 @@ pub fn kernel_init() -> KernelResult<()> {
                         crate::graphics::fbcon::FbPixelFormat::Rgb,
                     );
                 }
-                kprintln!("[BOOTSTRAP] ramfb + fbcon initialized (1024x768)");
+                klog!(Info, "bootstrap", "ramfb + fbcon initialized (1024x768)");
             }
             Err(_) => {
-                kprintln!("[BOOTSTRAP] ramfb not available, serial-only output");
+                klog!(Warn, "bootstrap", "ramfb not available, serial-only output");
             }
         }
     }
@@ -363,12 +399,12 @@ pub fn kernel_init() -> KernelResult<()> {
     // This ensures the RNG is ready before any security module needs it.
     #[cfg(target_arch = "x86_64")]
     {
-        kprintln!("[BOOTSTRAP] Pre-initializing CSPRNG...");
+        klog!(Info, "bootstrap", "Pre-initializing CSPRNG...");
         let _ = crate::crypto::random::init();
         // Verify the RNG works
         let rng = crate::crypto::random::get_random();
         let v = rng.next_u64();
-        crate::println!("[BOOTSTRAP] CSPRNG initialized (test: {})", v);
+        klog!(Info, "bootstrap", "CSPRNG initialized (test: {})", v);
     }
 
     // x86_64: The UEFI-provided boot stack is 128KB. In debug mode, deep
@@ -404,51 +440,63 @@ pub fn kernel_init() -> KernelResult<()> {
 /// call it directly from `kernel_init`.
 fn kernel_init_stage3_impl() -> KernelResult<()> {
     // Stage 3: Process management
-    kprintln!("[BOOTSTRAP] Stage 3: Process management");
+    klog!(Info, "bootstrap", "Stage 3: Process management");
+
+    // Size process/thread/file/IPC limits from RAM and the command line
+    // before anything allocates against them
+    crate::limits::init();
 
     process::init_without_init_process().expect("Failed to initialize process management");
 
-    kprintln!("[BOOTSTRAP] Process management initialized");
+    klog!(Info, "bootstrap", "Process management initialized");
 
     // Stage 4: Core kernel services
-    kprintln!("[BOOTSTRAP] Stage 4: Kernel services");
+    klog!(Info, "bootstrap", "Stage 4: Kernel services");
 
-    kprintln!("[BOOTSTRAP] Initializing capabilities...");
+    klog!(Info, "bootstrap", "Initializing capabilities...");
     cap::init();
-    kprintln!("[BOOTSTRAP] Capabilities initialized");
+    klog!(Info, "bootstrap", "Capabilities initialized");
 
     // Initialize security modules individually to minimize stack depth.
     // Each module's init() constructs its state on the stack before moving
     // into a static OnceLock/Mutex. Calling them individually (rather than
     // through security::init()) avoids accumulating stack frames.
-    kprintln!("[BOOTSTRAP] Initializing security subsystem...");
+    klog!(Info, "bootstrap", "Initializing security subsystem...");
     security::memory_protection::init().expect("Failed to initialize memory protection");
     security::auth::init().expect("Failed to initialize auth");
     security::tpm::init().expect("Failed to initialize TPM");
     security::mac::init().expect("Failed to initialize MAC");
     security::audit::init().expect("Failed to initialize audit");
     let _ = security::boot::verify();
-    kprintln!("[BOOTSTRAP] Security subsystem initialized");
+    klog!(Info, "bootstrap", "Security subsystem initialized");
 
-    kprintln!("[BOOTSTRAP] Initializing performance monitoring...");
+    // GDB remote stub on COM2, when requested with `debugstub`
+    #[cfg(all(feature = "alloc", target_arch = "x86_64"))]
+    if crate::debug::gdb_stub::DEBUGSTUB.get() {
+        crate::debug::gdb_stub::gdb_init();
+    }
+
+    klog!(Info, "bootstrap", "Initializing performance monitoring...");
     perf::init().expect("Failed to initialize performance monitoring");
     // Initialize hardware performance counters (PMU) after ACPI/APIC setup.
     crate::perf::pmu::init();
-    kprintln!(
-        "[BOOTSTRAP] Performance monitoring initialized (PMU: {} counters)",
+    klog!(
+        Info,
+        "bootstrap",
+        "Performance monitoring initialized (PMU: {} counters)",
         crate::perf::pmu::num_counters()
     );
 
-    kprintln!("[BOOTSTRAP] Initializing IPC...");
+    klog!(Info, "bootstrap", "Initializing IPC...");
     ipc::init();
-    kprintln!("[BOOTSTRAP] IPC initialized");
+    klog!(Info, "bootstrap", "IPC initialized");
 
     // Initialize VFS and mount essential filesystems
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing VFS...");
+        klog!(Info, "bootstrap", "Initializing VFS...");
         fs::init();
-        kprintln!("[BOOTSTRAP] VFS initialized");
+        klog!(Info, "bootstrap", "VFS initialized");
     }
 
     // Populate the RamFS with embedded init and shell binaries so that
@@ -456,11 +504,15 @@ fn kernel_init_stage3_impl() -> KernelResult<()> {
     // /bin/vsh instead of falling back to stub processes.
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Populating initramfs with embedded binaries...");
+        klog!(
+            Info,
+            "bootstrap",
+            "Populating initramfs with embedded binaries..."
+        );
         if crate::userspace::embedded::populate_initramfs().is_err() {
-            kprintln!("[BOOTSTRAP] Warning: Failed to populate initramfs");
+            klog!(Warn, "bootstrap", "Warning: Failed to populate initramfs");
         } else {
-            kprintln!("[BOOTSTRAP] Initramfs populated successfully");
+            klog!(Info, "bootstrap", "Initramfs populated successfully");
         }
     }
 
@@ -470,11 +522,11 @@ fn kernel_init_stage3_impl() -> KernelResult<()> {
     // Must happen after VFS init so TAR loading can populate the filesystem.
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing drivers + virtio-blk...");
+        klog!(Info, "bootstrap", "Initializing drivers + virtio-blk...");
         services::driver_framework::init();
 
         // PCI bus enumeration is x86_64-only. AArch64/RISC-V use MMIO transport
-        // for virtio devices (probed in blk::init() via init_mmio()). The I/O
+        // for virtio devices (bound by services::devmgr). The I/O
         // port stubs on non-x86 return 0, which would make every PCI slot appear
         // populated (vendor_id 0 != 0xFFFF), causing 8192 phantom device scans.
         #[cfg(target_arch = "x86_64")]
@@ -489,14 +541,39 @@ fn kernel_init_stage3_impl() -> KernelResult<()> {
             crate::drivers::pci::probe_known_drivers();
         }
 
-        // blk::init() dispatches to PCI probe on x86_64, MMIO probe on
-        // AArch64/RISC-V.
-        crate::drivers::virtio::blk::init();
+        // Bind the drivers built into the kernel, virtio-blk among them, to
+        // the PCI or device tree devices their manifests match.
+        services::devmgr::init();
+
+        // Null block devices and RAM disks; loop devices are attached later
+        // on demand.
+        crate::drivers::block::init();
+
+        // UART drivers and /dev/ttyS*; from here on the console output goes
+        // through the console multiplexer.
+        crate::drivers::serial::init();
 
         // Initialize PS/2 mouse driver (x86_64: aux port, others: stub)
         crate::drivers::mouse::init();
 
-        kprintln!("[BOOTSTRAP] Drivers + virtio-blk initialized");
+        klog!(Info, "bootstrap", "Drivers + virtio-blk initialized");
+
+        // Look up the reset and power off mechanisms before anything below
+        // may need to reset the machine.
+        crate::power::system::init();
+
+        // C-state and P-state tables for the idle loop and cpufreq governor.
+        if let Err(e) = crate::power::init() {
+            klog!(Warn, "bootstrap", "Power management init failed: {:?}", e);
+        }
+
+        // Count this boot against a pending A/B kernel slot as early as
+        // possible; rolls back (and resets) if it has no attempts left.
+        crate::bootslot::init();
+
+        // Find the crash dump partition and report a crash of the previous
+        // boot.
+        crate::crash::init();
 
         // If a virtio-blk disk is attached, read it as a TAR archive
         // and load its contents into the VFS. This is how cross-compiled
@@ -507,19 +584,24 @@ fn kernel_init_stage3_impl() -> KernelResult<()> {
     // Initialize services (process server, driver framework, etc.)
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing services...");
+        klog!(Info, "bootstrap", "Initializing services...");
         services::init();
-        kprintln!("[BOOTSTRAP] Services initialized");
+        klog!(Info, "bootstrap", "Services initialized");
 
         // Activate init system services
         if let Some(init) = crate::services::init_system::try_get_init_system() {
             if let Err(_e) = init.initialize() {
-                kprintln!("[BOOTSTRAP] Init system activation deferred: {:?}", _e);
+                klog!(
+                    Info,
+                    "bootstrap",
+                    "Init system activation deferred: {:?}",
+                    _e
+                );
             }
         }
     }
 
-    kprintln!("[BOOTSTRAP] Core services initialized");
+    klog!(Info, "bootstrap", "Core services initialized");
 
     // x86_64: Initialize keyboard driver state (decoder) so boot tests
     // can verify it. IRQ unmask + interrupt enable happen later (Stage 6,
@@ -527,124 +609,160 @@ fn kernel_init_stage3_impl() -> KernelResult<()> {
     #[cfg(target_arch = "x86_64")]
     {
         crate::drivers::keyboard::init();
-        kprintln!("[BOOTSTRAP] Keyboard driver initialized");
+        klog!(Info, "bootstrap", "Keyboard driver initialized");
     }
 
     // Run kernel-mode init tests after Stage 4 (VFS + shell ready)
     kernel_init_main();
 
     // Stage 5: Scheduler initialization
-    kprintln!("[BOOTSTRAP] Stage 5: Scheduler activation");
+    klog!(Info, "bootstrap", "Stage 5: Scheduler activation");
 
     sched::init();
 
-    // Initialize package manager
+    // Deferred work: one kworker per online CPU
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing package manager...");
+        sched::workqueue::init();
+        mm::ksm::init();
+    }
+
+    // Initialize package manager
+    #[cfg(all(feature = "alloc", feature = "pkg"))]
+    {
+        klog!(Info, "bootstrap", "Initializing package manager...");
         pkg::init();
-        kprintln!("[BOOTSTRAP] Package manager initialized");
-        kprintln!("[PKGMGR] Package manager v0.4.0 ready");
+        klog!(Info, "bootstrap", "Package manager initialized");
+        klog!(Info, "pkgmgr", "Package manager v0.4.0 ready");
     }
 
     // Initialize network stack
-    #[cfg(feature = "alloc")]
+    #[cfg(all(feature = "alloc", feature = "net"))]
     {
-        kprintln!("[BOOTSTRAP] Initializing network stack...");
+        klog!(Info, "bootstrap", "Initializing network stack...");
         net::init().expect("Failed to initialize network stack");
-        kprintln!("[BOOTSTRAP] Network stack initialized");
+        klog!(Info, "bootstrap", "Network stack initialized");
+
+        // Remote syslog forwarding if /etc/syslog.toml exists (non-fatal
+        // when absent or invalid)
+        #[cfg(feature = "pkg")]
+        if crate::log_service::syslog::load_config(crate::log_service::syslog::CONFIG_PATH).is_ok()
+        {
+            klog!(Info, "bootstrap", "Syslog forwarding enabled");
+        }
     }
 
     // Initialize graphics subsystem
-    kprintln!("[BOOTSTRAP] Initializing graphics subsystem...");
+    klog!(Info, "bootstrap", "Initializing graphics subsystem...");
     graphics::init().expect("Failed to initialize graphics");
-    kprintln!("[BOOTSTRAP] Graphics subsystem initialized");
+    klog!(Info, "bootstrap", "Graphics subsystem initialized");
 
     // Initialize IRQ manager and timer wheel (needed by drivers and scheduler)
     #[cfg(feature = "alloc")]
     {
         if let Err(_e) = irq::init() {
-            kprintln!("[BOOTSTRAP] IRQ manager init skipped (already initialized)");
+            klog!(
+                Info,
+                "bootstrap",
+                "IRQ manager init skipped (already initialized)"
+            );
         }
         if let Err(_e) = timer::init() {
-            kprintln!("[BOOTSTRAP] Timer wheel init skipped (already initialized)");
+            klog!(
+                Info,
+                "bootstrap",
+                "Timer wheel init skipped (already initialized)"
+            );
         }
     }
 
     // Initialize USB subsystem (placeholder controllers, non-fatal)
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing USB subsystem...");
+        klog!(Info, "bootstrap", "Initializing USB subsystem...");
         crate::drivers::usb::init();
-        kprintln!("[BOOTSTRAP] USB subsystem initialized");
+        klog!(Info, "bootstrap", "USB subsystem initialized");
     }
 
     // Initialize persistent user database
     #[cfg(feature = "alloc")]
     {
         crate::syscall::userland_ext::users::init_user_db();
-        kprintln!("[BOOTSTRAP] User database initialized");
+        klog!(Info, "bootstrap", "User database initialized");
     }
 
     // Initialize PTY subsystem (needed by desktop terminal emulator)
     #[cfg(feature = "alloc")]
     {
         if let Err(_e) = crate::fs::pty::init() {
-            kprintln!("[BOOTSTRAP] PTY init failed (non-fatal)");
+            klog!(Warn, "bootstrap", "PTY init failed (non-fatal)");
         }
     }
 
     // Initialize desktop subsystem (Wayland, window manager, apps)
-    #[cfg(feature = "alloc")]
+    #[cfg(all(feature = "alloc", feature = "desktop"))]
     {
-        kprintln!("[BOOTSTRAP] Initializing desktop subsystem...");
+        klog!(Info, "bootstrap", "Initializing desktop subsystem...");
         if let Err(_e) = desktop::init() {
-            kprintln!("[BOOTSTRAP] Desktop init deferred (non-fatal)");
+            klog!(Warn, "bootstrap", "Desktop init deferred (non-fatal)");
         }
         // Initialize notification manager with screen dimensions from fbcon
         if let Some(hw) = graphics::fbcon::get_hw_info() {
             crate::desktop::notification::init(hw.width, hw.height);
         }
-        kprintln!("[BOOTSTRAP] Desktop subsystem initialized");
+        klog!(Info, "bootstrap", "Desktop subsystem initialized");
     }
 
     // Initialize audio subsystem (mixer, pipeline, VirtIO-Sound)
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing audio subsystem...");
+        klog!(Info, "bootstrap", "Initializing audio subsystem...");
         if let Err(_e) = audio::init() {
-            kprintln!("[BOOTSTRAP] Audio init deferred (non-fatal)");
+            klog!(Warn, "bootstrap", "Audio init deferred (non-fatal)");
         }
-        kprintln!("[BOOTSTRAP] Audio subsystem initialized");
+        klog!(Info, "bootstrap", "Audio subsystem initialized");
     }
 
     // Initialize video subsystem (decoders, player)
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Initializing video subsystem...");
+        klog!(Info, "bootstrap", "Initializing video subsystem...");
         if let Err(_e) = video::init() {
-            kprintln!("[BOOTSTRAP] Video init deferred (non-fatal)");
+            klog!(Warn, "bootstrap", "Video init deferred (non-fatal)");
         }
-        kprintln!("[BOOTSTRAP] Video subsystem initialized");
+        klog!(Info, "bootstrap", "Video subsystem initialized");
     }
 
     // Initialize virtualization subsystem (VMX detection, containers)
     #[cfg(target_arch = "x86_64")]
     {
-        kprintln!("[BOOTSTRAP] Initializing virtualization subsystem...");
+        klog!(
+            Info,
+            "bootstrap",
+            "Initializing virtualization subsystem..."
+        );
         virt::init();
-        kprintln!("[BOOTSTRAP] Virtualization subsystem initialized");
+        klog!(Info, "bootstrap", "Virtualization subsystem initialized");
     }
 
     // Initialize KPTI shadow page tables (Meltdown mitigation)
     #[cfg(target_arch = "x86_64")]
     {
-        kprintln!("[BOOTSTRAP] Initializing KPTI shadow page tables...");
+        klog!(Info, "bootstrap", "Initializing KPTI shadow page tables...");
         crate::arch::x86_64::kpti::init();
-        kprintln!("[BOOTSTRAP] KPTI initialized");
+        klog!(Info, "bootstrap", "KPTI initialized");
     }
 
-    kprintln!("[BOOTSTRAP] Scheduler activated - entering main scheduling loop");
+    klog!(
+        Info,
+        "bootstrap",
+        "Scheduler activated - entering main scheduling loop"
+    );
+
+    // User-space drivers from /drivers, for the devices no built-in driver
+    // took.
+    #[cfg(feature = "alloc")]
+    services::devmgr::start_user_drivers();
 
     // Phase 4A: Try to load a user-space binary from the rootfs.
     // This is the critical gate for self-hosting -- verifies that cross-compiled
@@ -680,42 +798,53 @@ pub fn run() -> ! {
     }
 
     // Stage 6: User space transition
-    kprintln!("[BOOTSTRAP] Stage 6: User space transition");
+    klog!(Info, "bootstrap", "Stage 6: User space transition");
 
-    kprintln!("[BOOTSTRAP] About to create init process...");
+    klog!(Info, "bootstrap", "About to create init process...");
     create_init_process();
-    kprintln!("[BOOTSTRAP] Init process created");
+    klog!(Info, "bootstrap", "Init process created");
 
     // Mark Stage 6 complete
-    kprintln!("[BOOTSTRAP] User space transition prepared");
-    kprintln!("[KERNEL] Boot sequence complete!");
+    klog!(Info, "bootstrap", "User space transition prepared");
+    klog!(Info, "kernel", "Boot sequence complete!");
     kprintln!("BOOTOK");
 
     // Attempt user-mode entry. On success, transitions to user-space
     // and never returns. On failure, falls through to the interactive shell.
     #[cfg(target_arch = "aarch64")]
     {
-        kprintln!("[BOOTSTRAP] Attempting user-mode entry...");
+        klog!(Info, "bootstrap", "Attempting user-mode entry...");
         if crate::arch::aarch64::usermode::try_enter_usermode().is_err() {
-            kprintln!("[BOOTSTRAP] User-mode entry deferred (prerequisites not met)");
+            klog!(
+                Info,
+                "bootstrap",
+                "User-mode entry deferred (prerequisites not met)"
+            );
         }
     }
     #[cfg(target_arch = "riscv64")]
     {
-        kprintln!("[BOOTSTRAP] Attempting user-mode entry...");
+        klog!(Info, "bootstrap", "Attempting user-mode entry...");
         if crate::arch::riscv64::usermode::try_enter_usermode().is_err() {
-            kprintln!("[BOOTSTRAP] User-mode entry deferred (prerequisites not met)");
+            klog!(
+                Info,
+                "bootstrap",
+                "User-mode entry deferred (prerequisites not met)"
+            );
         }
     }
 
     // Enable framebuffer console output now that boot is complete.
     graphics::fbcon::enable_output();
 
+    // Boot is done; from here a hung scheduler resets the machine.
+    crate::drivers::watchdog::start_hardware();
+
     // Launch the interactive kernel shell (never returns).
     // The shell provides a serial console REPL for all 3 architectures.
     #[cfg(feature = "alloc")]
     {
-        kprintln!("[BOOTSTRAP] Starting interactive shell...");
+        klog!(Info, "bootstrap", "Starting interactive shell...");
         crate::services::shell::run_shell();
     }
 
@@ -724,6 +853,12 @@ pub fn run() -> ! {
     sched::start();
 }
 
+#[cfg(feature = "alloc")]
+crate::kernel_param! {
+    /// Root filesystem: `PARTLABEL=<name>`, `/dev/vda` or `none` (default: auto-detect).
+    static ROOT: alloc::string::String = alloc::string::String::new(), "root";
+}
+
 /// Load a rootfs TAR archive from virtio-blk into the VFS.
 ///
 /// If a virtio-blk device is attached (via QEMU `-drive ... -device
@@ -733,51 +868,112 @@ pub fn run() -> ! {
 ///   persistent BlockFS root filesystem (replacing the initial RamFS).
 /// - Otherwise, read the entire disk as a TAR archive and load into RamFS
 ///   (existing behavior).
+///
+/// A GPT disk is never read as a whole: on an A/B boot disk (see
+/// `bootslot`) or a combined boot + root disk built with
+/// `bootimage-builder --rootfs-dir`, the `veridian-rootfs` partition, if
+/// present, is probed the same way.
+///
+/// The `root=` parameter overrides the choice: `PARTLABEL=<name>` probes
+/// that GPT partition, `/dev/vda` the whole disk, and `none` leaves the boot
+/// root filesystem in place.
 #[cfg(feature = "alloc")]
 fn load_rootfs_from_disk() {
     use crate::drivers::virtio::blk;
 
+    let root = ROOT.get();
+    if root == "none" {
+        klog!(
+            Info,
+            "rootfs",
+            "root=none, keeping the boot root filesystem"
+        );
+        return;
+    }
+    if !root.is_empty() && root != "/dev/vda" && !root.starts_with("PARTLABEL=") {
+        klog!(
+            Warn,
+            "rootfs",
+            "root={} not recognized, auto-detecting",
+            root
+        );
+    }
+
     if !blk::is_initialized() {
-        kprintln!("[ROOTFS] No virtio-blk device, skipping disk load");
+        klog!(Info, "rootfs", "No virtio-blk device, skipping disk load");
         return;
     }
 
     let device = match blk::get_device() {
         Some(dev) => dev,
         None => {
-            kprintln!("[ROOTFS] virtio-blk device not available");
+            klog!(Warn, "rootfs", "virtio-blk device not available");
             return;
         }
     };
 
+    let (first, sectors) = if let Some(label) = root.strip_prefix("PARTLABEL=") {
+        let mut dev = device.lock();
+        match crate::bootslot::find_partition(&mut *dev, label) {
+            Some(part) => (part.first_lba, part.sectors),
+            None => {
+                klog!(Warn, "rootfs", "root={}: no such partition", root);
+                return;
+            }
+        }
+    } else if root == "/dev/vda" {
+        (0, u64::MAX)
+    } else if crate::bootslot::is_active() {
+        match crate::bootslot::rootfs_partition() {
+            Some(part) => (part.first_lba, part.sectors),
+            None => {
+                klog!(Info, "rootfs", "A/B boot disk has no rootfs partition");
+                return;
+            }
+        }
+    } else {
+        let mut dev = device.lock();
+        let partition =
+            crate::bootslot::find_partition(&mut *dev, crate::bootslot::ROOTFS_PARTITION);
+        match partition {
+            Some(part) => (part.first_lba, part.sectors),
+            None => (0, u64::MAX),
+        }
+    };
+
     // Probe the first sector (512 bytes) to check for BlockFS magic
     let mut probe_buf = [0u8; 512];
     {
         let mut dev = device.lock();
-        if let Err(_e) = dev.read_block(0, &mut probe_buf) {
-            kprintln!("[ROOTFS] Failed to read sector 0 for probe");
+        if let Err(_e) = dev.read_block(first, &mut probe_buf) {
+            klog!(Warn, "rootfs", "Failed to read sector {} for probe", first);
             return;
         }
     }
 
     let magic = u32::from_le_bytes([probe_buf[0], probe_buf[1], probe_buf[2], probe_buf[3]]);
     if magic == crate::fs::blockfs::BLOCKFS_MAGIC {
-        kprintln!("[ROOTFS] BlockFS magic detected -- mounting persistent root");
-        mount_blockfs_root();
+        klog!(
+            Info,
+            "rootfs",
+            "BlockFS magic detected -- mounting persistent root"
+        );
+        mount_blockfs_root(first, sectors);
         return;
     }
 
     // Fall back to TAR loading
-    load_tar_rootfs();
+    load_tar_rootfs(first, sectors);
 }
 
-/// Mount a pre-formatted BlockFS image as the persistent root filesystem.
+/// Mount a pre-formatted BlockFS image, `sectors` sectors starting at
+/// `first`, as the persistent root filesystem.
 ///
 /// Reads superblock, bitmap, inode table, and all data blocks from the
 /// virtio-blk device. Replaces the initial RamFS via `swap_root()`, then
 /// re-mounts DevFS at `/dev` and ProcFS at `/proc`.
 #[cfg(feature = "alloc")]
-fn mount_blockfs_root() {
+fn mount_blockfs_root(first: u64, sectors: u64) {
     use alloc::sync::Arc;
 
     use spin::Mutex;
@@ -790,19 +986,21 @@ fn mount_blockfs_root() {
         Permissions,
     };
 
-    let backend = Arc::new(Mutex::new(VirtioBlockBackend));
+    let backend = Arc::new(Mutex::new(VirtioBlockBackend::new(first, sectors)));
 
     let blockfs = match BlockFs::open_existing(backend) {
         Ok(fs) => {
-            kprintln!("[ROOTFS] BlockFS loaded successfully");
+            klog!(Info, "rootfs", "BlockFS loaded successfully");
             fs
         }
         Err(_e) => {
-            kprintln!(
-                "[ROOTFS] Failed to open BlockFS: {:?}, falling back to TAR rootfs",
+            klog!(
+                Warn,
+                "rootfs",
+                "Failed to open BlockFS: {:?}, falling back to TAR rootfs",
                 _e
             );
-            load_tar_rootfs();
+            load_tar_rootfs(first, sectors);
             return;
         }
     };
@@ -821,7 +1019,7 @@ fn mount_blockfs_root() {
         vfs_guard.swap_root(blockfs_arc);
     }
 
-    kprintln!("[ROOTFS] BlockFS mounted as persistent root");
+    klog!(Info, "rootfs", "BlockFS mounted as persistent root");
 
     // Ensure standard directories exist (may already exist from mkfs population)
     {
@@ -838,41 +1036,48 @@ fn mount_blockfs_root() {
     // Re-mount DevFS and ProcFS
     {
         let vfs = get_vfs();
-        let mut vfs_guard = vfs.write();
+        let vfs_guard = vfs.write();
         vfs_guard.mount("/dev".into(), Arc::new(DevFs::new())).ok();
         vfs_guard
             .mount("/proc".into(), Arc::new(ProcFs::new()))
             .ok();
     }
 
-    kprintln!("[ROOTFS] DevFS and ProcFS re-mounted on BlockFS root");
+    klog!(
+        Info,
+        "rootfs",
+        "DevFS and ProcFS re-mounted on BlockFS root"
+    );
 }
 
-/// Load the virtio-blk disk contents as a TAR archive into the RamFS.
+/// Load `sectors` sectors of the virtio-blk disk starting at `first` (clamped
+/// to the disk) as a TAR archive into the RamFS.
 /// This is the legacy boot path for non-persistent rootfs images.
 #[cfg(feature = "alloc")]
-fn load_tar_rootfs() {
+fn load_tar_rootfs(first: u64, sectors: u64) {
     use crate::drivers::virtio::blk;
 
     let device = match blk::get_device() {
         Some(dev) => dev,
         None => {
-            kprintln!("[ROOTFS] virtio-blk device not available");
+            klog!(Warn, "rootfs", "virtio-blk device not available");
             return;
         }
     };
 
     let mut dev = device.lock();
-    let total_sectors = dev.capacity_sectors();
+    let total_sectors = sectors.min(dev.capacity_sectors().saturating_sub(first));
     let total_bytes = total_sectors as usize * blk::BLOCK_SIZE;
 
     if total_sectors == 0 {
-        kprintln!("[ROOTFS] Disk is empty (0 sectors)");
+        klog!(Info, "rootfs", "Disk is empty (0 sectors)");
         return;
     }
 
-    kprintln!(
-        "[ROOTFS] Reading {} sectors ({} KB) from virtio-blk...",
+    klog!(
+        Info,
+        "rootfs",
+        "Reading {} sectors ({} KB) from virtio-blk...",
         total_sectors,
         total_bytes / 1024
     );
@@ -883,8 +1088,17 @@ fn load_tar_rootfs() {
     // Read all sectors
     for sector in 0..total_sectors {
         let offset = sector as usize * blk::BLOCK_SIZE;
-        if let Err(_e) = dev.read_block(sector, &mut disk_data[offset..offset + blk::BLOCK_SIZE]) {
-            kprintln!("[ROOTFS] Read error at sector {}/{}", sector, total_sectors);
+        if let Err(_e) = dev.read_block(
+            first + sector,
+            &mut disk_data[offset..offset + blk::BLOCK_SIZE],
+        ) {
+            klog!(
+                Warn,
+                "rootfs",
+                "Read error at sector {}/{}",
+                sector,
+                total_sectors
+            );
             return;
         }
     }
@@ -892,14 +1106,14 @@ fn load_tar_rootfs() {
     // Release the device lock before calling into VFS
     drop(dev);
 
-    kprintln!("[ROOTFS] Disk read complete, parsing TAR archive...");
+    klog!(Info, "rootfs", "Disk read complete, parsing TAR archive...");
 
     match crate::fs::tar::load_tar_to_vfs(&disk_data) {
         Ok(_count) => {
-            kprintln!("[ROOTFS] Loaded entries from disk into VFS");
+            klog!(Info, "rootfs", "Loaded entries from disk into VFS");
         }
         Err(_e) => {
-            kprintln!("[ROOTFS] TAR parse error");
+            klog!(Warn, "rootfs", "TAR parse error");
         }
     }
 }
@@ -1662,13 +1876,12 @@ pub fn boot_run_forked_child(
     // Set child as the current boot process
     crate::process::set_boot_current(child_pid, child_tid);
 
-    // Rebalance swapgs: we're inside a syscall handler where GS.base = per_cpu_data
-    // and KernelGsBase = 0 (from parent's syscall_entry swapgs). enter_usermode
-    // needs KernelGsBase = per_cpu_data so the child's syscall_entry can load
-    // kernel_rsp. swapgs swaps them: GS.base → 0, KernelGsBase → per_cpu_data.
-    // SAFETY: Rebalancing swapgs so KernelGsBase holds per_cpu_data for the
-    // child's syscall_entry. We are in a syscall context with known GS state.
-    unsafe { core::arch::asm!("swapgs", options(nomem, nostack)) };
+    // enter_usermode needs KernelGsBase = per_cpu_data so the child's
+    // syscall_entry can load kernel_rsp. Both GS bases normally hold it (see
+    // sched::percpu); reinstall them in case the parent loaded its own GS
+    // and syscall_entry's swapgs left that in KernelGsBase.
+    let cpu = crate::sched::percpu::cpu_id();
+    crate::sched::percpu::init_cpu(cpu);
 
     let kernel_rsp_ptr = per_cpu as u64;
 
@@ -1694,13 +1907,10 @@ pub fn boot_run_forked_child(
         crate::arch::x86_64::usermode::enter_forked_child_returnable(&regs, cr3, kernel_rsp_ptr);
     }
 
-    // Child exited, boot_return_to_kernel brought us back here.
-    // boot_return_to_kernel did swapgs (balancing child's syscall_entry swapgs)
-    // and zeroed GS. Now: GS.base=0, KernelGsBase=per_cpu_data.
-    // swapgs to restore parent's syscall handler state: GS.base=per_cpu_data.
-    // SAFETY: Restoring GS.base to per_cpu_data after child exit. The GS state
-    // is known (GS.base=0, KernelGsBase=per_cpu_data) from boot_return_to_kernel.
-    unsafe { core::arch::asm!("swapgs", options(nomem, nostack)) };
+    // Child exited, boot_return_to_kernel brought us back here. Its swapgs
+    // balanced the child's syscall_entry; reinstall the per-CPU GS bases in
+    // case the child loaded its own GS.
+    crate::sched::percpu::init_cpu(cpu);
 
     // Boot CR3 is restored. Free the child's page table hierarchy frames
     // (deferred from cleanup_process -- see vas.rs clear() comment).
@@ -1778,6 +1988,7 @@ pub fn kernel_init_main() {
     run_elf_tests(&mut passed, &mut failed);
     run_capability_tests(&mut passed, &mut failed);
     run_security_tests(&mut passed, &mut failed);
+    #[cfg(feature = "pkg")]
     run_phase4_tests(&mut passed, &mut failed);
     run_display_tests(&mut passed, &mut failed);
 
@@ -2136,7 +2347,7 @@ fn run_security_tests(passed: &mut u32, failed: &mut u32) {
 }
 
 /// Run Phase 4 package ecosystem boot tests (tests 23-27).
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 fn run_phase4_tests(passed: &mut u32, failed: &mut u32) {
     kprintln!("[INIT] Phase 4 package ecosystem tests:");
 
@@ -2245,7 +2456,11 @@ fn create_init_process() {
         // BOOTOK) handles all memory setup and mode switching directly.
         #[cfg(target_arch = "x86_64")]
         {
-            kprintln!("[BOOTSTRAP] Skipping PCB creation (direct usermode path)");
+            klog!(
+                Info,
+                "bootstrap",
+                "Skipping PCB creation (direct usermode path)"
+            );
         }
 
         // On non-x86_64, use the ELF loader path (which creates a process
@@ -2254,7 +2469,7 @@ fn create_init_process() {
         {
             match crate::userspace::load_init_process() {
                 Ok(_init_pid) => {
-                    kprintln!("[BOOTSTRAP] Init process ready");
+                    klog!(Info, "bootstrap", "Init process ready");
 
                     // Skip on RISC-V: the bump allocator cannot free memory,
                     // so loading a second process needlessly consumes heap
@@ -2268,7 +2483,11 @@ fn create_init_process() {
                 Err(_e) => {
                     // Init process creation is non-critical — the kernel shell
                     // provides the interactive interface.
-                    kprintln!("[BOOTSTRAP] Init process deferred (kernel shell active)");
+                    klog!(
+                        Info,
+                        "bootstrap",
+                        "Init process deferred (kernel shell active)"
+                    );
                 }
             }
         }
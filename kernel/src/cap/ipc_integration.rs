@@ -159,5 +159,44 @@ pub fn create_endpoint_with_capability(
     let cap = create_endpoint_capability(endpoint_id, owner, rights, cap_space)
         .map_err(|_| IpcError::OutOfMemory)?;
 
+    // Bind the capability to the registry endpoint so the syscall layer can
+    // resolve it (delegated copies share the capability ID)
+    crate::ipc::registry::bind_capability(cap.id(), endpoint_id)?;
+
     Ok((endpoint_id, cap))
 }
+
+/// Mint a new capability for an existing endpoint into `cap_space`.
+///
+/// Used by the name server: a successful lookup grants the caller a
+/// capability carrying only `rights`, never the owner's full set.
+pub fn mint_endpoint_capability(
+    endpoint_id: EndpointId,
+    rights: Rights,
+    cap_space: &CapabilitySpace,
+) -> Result<CapabilityToken, IpcError> {
+    let owner = crate::ipc::registry::lookup_endpoint(endpoint_id)?.owner;
+    let cap = create_endpoint_capability(endpoint_id, owner, rights, cap_space)
+        .map_err(|_| IpcError::OutOfMemory)?;
+    crate::ipc::registry::bind_capability(cap.id(), endpoint_id)?;
+    Ok(cap)
+}
+
+/// Resolve an endpoint capability at the syscall boundary.
+///
+/// Checks that `cap` is present in `cap_space` with `required` rights and
+/// has not been revoked, then returns the endpoint it is bound to. Knowing
+/// an endpoint number is not enough: a capability that was never bound to
+/// an endpoint resolves to `InvalidCapability`.
+pub fn resolve_endpoint(
+    cap: CapabilityToken,
+    required: Rights,
+    cap_space: &CapabilitySpace,
+) -> Result<EndpointId, IpcError> {
+    match super::manager::check_capability(cap, required, cap_space) {
+        Ok(()) => {}
+        Err(CapError::InsufficientRights) => return Err(IpcError::PermissionDenied),
+        Err(_) => return Err(IpcError::InvalidCapability),
+    }
+    crate::ipc::registry::endpoint_for_capability(cap.id())
+}
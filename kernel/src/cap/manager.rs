@@ -361,11 +361,13 @@ pub fn check_capability(
 ) -> Result<(), CapError> {
     // Check if capability exists and has required rights
     if !cap_space.check_rights(cap, required_rights) {
+        crate::security::cap_audit::record_check_failure(cap, required_rights, cap_space);
         return Err(CapError::InsufficientRights);
     }
 
     // Check if not revoked
     if !cap_manager().is_valid(cap) {
+        crate::security::cap_audit::record_check_failure(cap, required_rights, cap_space);
         return Err(CapError::CapabilityRevoked);
     }
 
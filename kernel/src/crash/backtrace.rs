@@ -0,0 +1,254 @@
+//! Register snapshots and frame-pointer stack walks
+//!
+//! The kernel is built with frame pointers (`"frame-pointer": "always"` in
+//! the target specs, `-C force-frame-pointers=yes` in `build-kernel.sh`), so
+//! every frame records the caller's frame pointer next to its return
+//! address:
+//!
+//! - x86_64 and AArch64: `[fp]` is the caller's frame pointer, `[fp + 8]` the
+//!   return address.
+//! - RISC-V: `[fp - 16]` is the caller's frame pointer, `[fp - 8]` the return
+//!   address.
+//!
+//! The walk only follows frame pointers that are aligned, strictly
+//! increasing and within [`MAX_STACK`] bytes above the stack pointer it
+//! started from. That stops it at the end of the chain or at a corrupted
+//! frame, but it cannot tell a mapped stack page from an unmapped one.
+
+/// Deepest backtrace recorded.
+pub const MAX_FRAMES: usize = 32;
+
+/// How far above the starting stack pointer a frame may lie.
+pub const MAX_STACK: u64 = 1 << 20;
+
+#[cfg(target_arch = "riscv64")]
+const PREV_FP_OFFSET: i64 = -16;
+#[cfg(target_arch = "riscv64")]
+const RETURN_OFFSET: i64 = -8;
+#[cfg(not(target_arch = "riscv64"))]
+const PREV_FP_OFFSET: i64 = 0;
+#[cfg(not(target_arch = "riscv64"))]
+const RETURN_OFFSET: i64 = 8;
+
+/// Named register values captured at the crash site.
+pub struct Registers {
+    regs: [(&'static str, u64); 8],
+    len: usize,
+}
+
+impl Registers {
+    fn push(&mut self, name: &'static str, value: u64) {
+        if self.len < self.regs.len() {
+            self.regs[self.len] = (name, value);
+            self.len += 1;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.regs[..self.len].iter().copied()
+    }
+
+    /// Value of the frame pointer register.
+    pub fn frame_pointer(&self) -> u64 {
+        self.get(FP_NAME)
+    }
+
+    /// Value of the stack pointer register.
+    pub fn stack_pointer(&self) -> u64 {
+        self.get("sp")
+    }
+
+    fn get(&self, name: &str) -> u64 {
+        self.iter().find(|&(n, _)| n == name).map_or(0, |(_, v)| v)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+const FP_NAME: &str = "rbp";
+#[cfg(target_arch = "aarch64")]
+const FP_NAME: &str = "x29";
+#[cfg(target_arch = "riscv64")]
+const FP_NAME: &str = "s0";
+
+/// Capture the stack and frame pointers and the control registers that
+/// describe the CPU's state.
+#[inline(always)]
+pub fn capture() -> Registers {
+    let mut regs = Registers {
+        regs: [("", 0); 8],
+        len: 0,
+    };
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let (sp, fp, flags): (u64, u64, u64);
+        let (cr0, cr2, cr3, cr4): (u64, u64, u64, u64);
+        // SAFETY: Only reads registers. Reading control registers requires
+        // ring 0, which the kernel runs in; pushfq uses one stack slot.
+        unsafe {
+            core::arch::asm!("mov {}, rsp", out(reg) sp, options(nomem, nostack));
+            core::arch::asm!("mov {}, rbp", out(reg) fp, options(nomem, nostack));
+            core::arch::asm!("pushfq; pop {}", out(reg) flags, options(nomem));
+            core::arch::asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack));
+            core::arch::asm!("mov {}, cr2", out(reg) cr2, options(nomem, nostack));
+            core::arch::asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack));
+            core::arch::asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack));
+        }
+        regs.push("sp", sp);
+        regs.push("rbp", fp);
+        regs.push("rflags", flags);
+        regs.push("cr0", cr0);
+        regs.push("cr2", cr2);
+        regs.push("cr3", cr3);
+        regs.push("cr4", cr4);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        let (sp, fp, lr): (u64, u64, u64);
+        let (esr, far, elr, ttbr): (u64, u64, u64, u64);
+        // SAFETY: Only reads registers; the EL1 system registers are
+        // accessible because the kernel runs at EL1.
+        unsafe {
+            core::arch::asm!("mov {}, sp", out(reg) sp, options(nomem, nostack));
+            core::arch::asm!("mov {}, x29", out(reg) fp, options(nomem, nostack));
+            core::arch::asm!("mov {}, x30", out(reg) lr, options(nomem, nostack));
+            core::arch::asm!("mrs {}, esr_el1", out(reg) esr, options(nomem, nostack));
+            core::arch::asm!("mrs {}, far_el1", out(reg) far, options(nomem, nostack));
+            core::arch::asm!("mrs {}, elr_el1", out(reg) elr, options(nomem, nostack));
+            core::arch::asm!("mrs {}, ttbr1_el1", out(reg) ttbr, options(nomem, nostack));
+        }
+        regs.push("sp", sp);
+        regs.push("x29", fp);
+        regs.push("x30", lr);
+        regs.push("esr", esr);
+        regs.push("far", far);
+        regs.push("elr", elr);
+        regs.push("ttbr1", ttbr);
+    }
+
+    #[cfg(target_arch = "riscv64")]
+    {
+        let (sp, fp, ra): (u64, u64, u64);
+        let (sepc, scause, stval, satp): (u64, u64, u64, u64);
+        // SAFETY: Only reads registers; the supervisor CSRs are accessible
+        // because the kernel runs in S-mode.
+        unsafe {
+            core::arch::asm!("mv {}, sp", out(reg) sp, options(nomem, nostack));
+            core::arch::asm!("mv {}, s0", out(reg) fp, options(nomem, nostack));
+            core::arch::asm!("mv {}, ra", out(reg) ra, options(nomem, nostack));
+            core::arch::asm!("csrr {}, sepc", out(reg) sepc, options(nomem, nostack));
+            core::arch::asm!("csrr {}, scause", out(reg) scause, options(nomem, nostack));
+            core::arch::asm!("csrr {}, stval", out(reg) stval, options(nomem, nostack));
+            core::arch::asm!("csrr {}, satp", out(reg) satp, options(nomem, nostack));
+        }
+        regs.push("sp", sp);
+        regs.push("s0", fp);
+        regs.push("ra", ra);
+        regs.push("sepc", sepc);
+        regs.push("scause", scause);
+        regs.push("stval", stval);
+        regs.push("satp", satp);
+    }
+
+    regs
+}
+
+/// The frame and stack pointers of the calling function, to [`walk`] from
+/// without the cost of [`capture`].
+#[inline(always)]
+pub fn frame() -> (u64, u64) {
+    let (fp, sp): (u64, u64);
+    // SAFETY: Only copies the two registers.
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        core::arch::asm!(
+            "mov {}, rbp",
+            "mov {}, rsp",
+            out(reg) fp,
+            out(reg) sp,
+            options(nomem, nostack)
+        );
+        #[cfg(target_arch = "aarch64")]
+        core::arch::asm!(
+            "mov {}, x29",
+            "mov {}, sp",
+            out(reg) fp,
+            out(reg) sp,
+            options(nomem, nostack)
+        );
+        #[cfg(target_arch = "riscv64")]
+        core::arch::asm!(
+            "mv {}, s0",
+            "mv {}, sp",
+            out(reg) fp,
+            out(reg) sp,
+            options(nomem, nostack)
+        );
+    }
+    (fp, sp)
+}
+
+/// Walk the frame-pointer chain from `fp`, calling `f` with each return
+/// address, innermost first. `sp` bounds the walk (see the module docs).
+/// Returns the number of frames visited.
+pub fn walk(mut fp: u64, sp: u64, mut f: impl FnMut(u64)) -> usize {
+    let limit = sp.saturating_add(MAX_STACK);
+    let mut frames = 0;
+    while frames < MAX_FRAMES {
+        let slot = fp.wrapping_add(PREV_FP_OFFSET as u64);
+        if !fp.is_multiple_of(8) || slot < sp || slot.saturating_add(16) > limit {
+            break;
+        }
+        // SAFETY: The frame lies within MAX_STACK of the live stack pointer
+        // and is aligned; see the module docs for what is not checked.
+        let (prev, ret) = unsafe {
+            (
+                core::ptr::read_volatile(slot as *const u64),
+                core::ptr::read_volatile(fp.wrapping_add(RETURN_OFFSET as u64) as *const u64),
+            )
+        };
+        if ret == 0 {
+            break;
+        }
+        f(ret);
+        frames += 1;
+        if prev <= fp {
+            break;
+        }
+        fp = prev;
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn test_walk_follows_chain() {
+        // Three frames in a fake stack, each linking to the next one up.
+        let mut stack = vec![0u64; 64];
+        let base = stack.as_ptr() as u64;
+        let fps = [base + 8 * 8, base + 24 * 8, base + 40 * 8];
+        let rets = [0x1111, 0x2222, 0x3333];
+        for (i, &fp) in fps.iter().enumerate() {
+            let word = |off: i64| ((fp as i64 + off - base as i64) / 8) as usize;
+            stack[word(PREV_FP_OFFSET)] = fps.get(i + 1).copied().unwrap_or(0);
+            stack[word(RETURN_OFFSET)] = rets[i];
+        }
+        let mut seen = Vec::new();
+        assert_eq!(walk(fps[0], base, |ret| seen.push(ret)), 3);
+        assert_eq!(seen, rets);
+    }
+
+    #[test]
+    fn test_walk_rejects_bad_frame_pointer() {
+        let stack = vec![0u64; 8];
+        let base = stack.as_ptr() as u64;
+        assert_eq!(walk(base + 3, base, |_| {}), 0);
+        assert_eq!(walk(0, base, |_| {}), 0);
+    }
+}
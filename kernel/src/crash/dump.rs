@@ -0,0 +1,346 @@
+//! Crash dump format
+//!
+//! A dump is a 64-byte header followed by a list of sections. All integers
+//! are little-endian.
+//!
+//! | Offset | Size | Header field                                     |
+//! |--------|------|--------------------------------------------------|
+//! | 0      | 4    | magic `VCRD`                                     |
+//! | 4      | 2    | format version ([`DUMP_VERSION`])                |
+//! | 6      | 2    | flags ([`FLAG_SEEN`])                            |
+//! | 8      | 4    | payload length (bytes after the header)          |
+//! | 12     | 4    | CRC-32 of the payload                            |
+//! | 16     | 8    | uptime at the crash, in milliseconds             |
+//! | 24     | 4    | CPU that crashed                                 |
+//! | 28     | 4    | architecture ([`ARCH_X86_64`], ...)              |
+//! | 32     | 32   | kernel version, NUL-padded                       |
+//!
+//! Each section is `kind: u16, reserved: u16, len: u32` and `len` bytes of
+//! data:
+//!
+//! - [`SECTION_MESSAGE`]: panic message, UTF-8
+//! - [`SECTION_REGISTERS`]: `{ name: [u8; 8], value: u64 }` pairs
+//! - [`SECTION_BACKTRACE`]: frames of `{ addr: u64, offset: u32, name_len: u32
+//!   }` followed by `name_len` bytes of symbol name (empty if unknown)
+//! - [`SECTION_LOG`]: the last kernel log lines, as `dmesg` text
+//!
+//! The payload is written once, so there is no torn-write protection beyond
+//! the CRC; a partially written dump is simply ignored.
+
+use core::fmt;
+
+use crate::bootslot::crc32;
+
+pub const DUMP_MAGIC: &[u8; 4] = b"VCRD";
+pub const DUMP_VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 64;
+
+/// The dump has been read and acknowledged by user space.
+pub const FLAG_SEEN: u16 = 1;
+
+pub const ARCH_X86_64: u32 = 1;
+pub const ARCH_AARCH64: u32 = 2;
+pub const ARCH_RISCV64: u32 = 3;
+
+pub const SECTION_MESSAGE: u16 = 1;
+pub const SECTION_REGISTERS: u16 = 2;
+pub const SECTION_BACKTRACE: u16 = 3;
+pub const SECTION_LOG: u16 = 4;
+
+const SECTION_HEADER_LEN: usize = 8;
+
+/// Architecture code of the running kernel.
+pub const fn current_arch() -> u32 {
+    if cfg!(target_arch = "x86_64") {
+        ARCH_X86_64
+    } else if cfg!(target_arch = "aarch64") {
+        ARCH_AARCH64
+    } else {
+        ARCH_RISCV64
+    }
+}
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn read_u64(buf: &[u8], off: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[off..off + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+// ---------------------------------------------------------------------------
+// Writing
+// ---------------------------------------------------------------------------
+
+/// Builds a dump in a caller-provided buffer without allocating.
+///
+/// Data that does not fit is dropped; sections are always closed, so the
+/// result stays parseable.
+pub struct DumpWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    /// Offset of the open section's header, if any.
+    section: Option<usize>,
+}
+
+impl<'a> DumpWriter<'a> {
+    /// Start a dump in `buf`, which must hold at least the header.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        assert!(buf.len() >= HEADER_LEN);
+        buf[..HEADER_LEN].fill(0);
+        Self {
+            buf,
+            len: HEADER_LEN,
+            section: None,
+        }
+    }
+
+    /// Close the current section (if any) and open one of `kind`.
+    pub fn begin(&mut self, kind: u16) {
+        self.end();
+        if self.len + SECTION_HEADER_LEN > self.buf.len() {
+            return;
+        }
+        self.buf[self.len..self.len + 2].copy_from_slice(&kind.to_le_bytes());
+        self.buf[self.len + 2..self.len + SECTION_HEADER_LEN].fill(0);
+        self.section = Some(self.len);
+        self.len += SECTION_HEADER_LEN;
+    }
+
+    /// Append raw bytes to the open section, truncating at the buffer end.
+    pub fn put(&mut self, data: &[u8]) {
+        if self.section.is_none() {
+            return;
+        }
+        let n = data.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&data[..n]);
+        self.len += n;
+    }
+
+    /// Append one register to a [`SECTION_REGISTERS`] section.
+    pub fn register(&mut self, name: &str, value: u64) {
+        let mut entry = [0u8; 16];
+        let n = name.len().min(8);
+        entry[..n].copy_from_slice(&name.as_bytes()[..n]);
+        entry[8..].copy_from_slice(&value.to_le_bytes());
+        self.put(&entry);
+    }
+
+    /// Append one frame to a [`SECTION_BACKTRACE`] section.
+    pub fn frame(&mut self, addr: u64, symbol: Option<(&str, u64)>) {
+        let (name, offset) = symbol.unwrap_or(("", 0));
+        let name = &name.as_bytes()[..name.len().min(255)];
+        let mut head = [0u8; 16];
+        head[..8].copy_from_slice(&addr.to_le_bytes());
+        head[8..12].copy_from_slice(&(offset as u32).to_le_bytes());
+        head[12..].copy_from_slice(&(name.len() as u32).to_le_bytes());
+        // A frame is only useful whole.
+        if self.len + head.len() + name.len() <= self.buf.len() {
+            self.put(&head);
+            self.put(name);
+        }
+    }
+
+    fn end(&mut self) {
+        if let Some(start) = self.section.take() {
+            let len = (self.len - start - SECTION_HEADER_LEN) as u32;
+            self.buf[start + 4..start + 8].copy_from_slice(&len.to_le_bytes());
+        }
+    }
+
+    /// Close the last section and fill in the header. Returns the dump
+    /// length.
+    pub fn finish(mut self, uptime_ms: u64, cpu: u32) -> usize {
+        self.end();
+        let payload_len = self.len - HEADER_LEN;
+        let crc = crc32(&self.buf[HEADER_LEN..self.len]);
+        let h = &mut self.buf[..HEADER_LEN];
+        h[0..4].copy_from_slice(DUMP_MAGIC);
+        h[4..6].copy_from_slice(&DUMP_VERSION.to_le_bytes());
+        h[8..12].copy_from_slice(&(payload_len as u32).to_le_bytes());
+        h[12..16].copy_from_slice(&crc.to_le_bytes());
+        h[16..24].copy_from_slice(&uptime_ms.to_le_bytes());
+        h[24..28].copy_from_slice(&cpu.to_le_bytes());
+        h[28..32].copy_from_slice(&current_arch().to_le_bytes());
+        let version = env!("CARGO_PKG_VERSION").as_bytes();
+        let n = version.len().min(31);
+        h[32..32 + n].copy_from_slice(&version[..n]);
+        self.len
+    }
+}
+
+impl fmt::Write for DumpWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.put(s.as_bytes());
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reading
+// ---------------------------------------------------------------------------
+
+/// Header fields of a stored dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DumpHeader {
+    pub flags: u16,
+    pub payload_len: u32,
+    pub uptime_ms: u64,
+    pub cpu: u32,
+    pub arch: u32,
+}
+
+impl DumpHeader {
+    /// Decode the header at the start of `buf`, or `None` if there is no
+    /// dump there.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        if buf.len() < HEADER_LEN || &buf[0..4] != DUMP_MAGIC || read_u16(buf, 4) != DUMP_VERSION {
+            return None;
+        }
+        Some(Self {
+            flags: read_u16(buf, 6),
+            payload_len: read_u32(buf, 8),
+            uptime_ms: read_u64(buf, 16),
+            cpu: read_u32(buf, 24),
+            arch: read_u32(buf, 28),
+        })
+    }
+
+    /// Total dump length, header included.
+    pub fn total_len(&self) -> usize {
+        HEADER_LEN + self.payload_len as usize
+    }
+}
+
+/// Check that `buf` holds a complete dump with a valid CRC; returns its
+/// header.
+pub fn validate(buf: &[u8]) -> Option<DumpHeader> {
+    let header = DumpHeader::decode(buf)?;
+    let payload = buf.get(HEADER_LEN..header.total_len())?;
+    (crc32(payload) == read_u32(buf, 12)).then_some(header)
+}
+
+/// Set or clear [`FLAG_SEEN`] in a dump header. The flags are outside the
+/// CRC, so only the header sector has to be rewritten.
+pub fn set_seen(buf: &mut [u8], seen: bool) {
+    let mut flags = read_u16(buf, 6);
+    if seen {
+        flags |= FLAG_SEEN;
+    } else {
+        flags &= !FLAG_SEEN;
+    }
+    buf[6..8].copy_from_slice(&flags.to_le_bytes());
+}
+
+/// Iterate over the `(kind, data)` sections of a validated dump.
+pub fn sections(buf: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    let end = DumpHeader::decode(buf).map_or(0, |h| h.total_len().min(buf.len()));
+    let mut off = HEADER_LEN;
+    core::iter::from_fn(move || {
+        if off + SECTION_HEADER_LEN > end {
+            return None;
+        }
+        let kind = read_u16(buf, off);
+        let len = read_u32(buf, off + 4) as usize;
+        let data = buf.get(off + SECTION_HEADER_LEN..off + SECTION_HEADER_LEN + len)?;
+        off += SECTION_HEADER_LEN + len;
+        Some((kind, data))
+    })
+}
+
+/// The panic message of a dump, if it has one.
+pub fn message(buf: &[u8]) -> Option<&str> {
+    sections(buf)
+        .find(|&(kind, _)| kind == SECTION_MESSAGE)
+        .and_then(|(_, data)| core::str::from_utf8(data).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write;
+
+    use super::*;
+
+    fn sample(buf: &mut [u8]) -> usize {
+        let mut w = DumpWriter::new(buf);
+        w.begin(SECTION_MESSAGE);
+        let _ = write!(w, "panicked at {}:{}", "mm/heap.rs", 42);
+        w.begin(SECTION_REGISTERS);
+        w.register("rip", 0xffff_8000_0010_0000);
+        w.register("rsp", 0xffff_8000_0020_0000);
+        w.begin(SECTION_BACKTRACE);
+        w.frame(
+            0xffff_8000_0010_0040,
+            Some(("veridian_kernel::mm::alloc", 0x40)),
+        );
+        w.frame(0x1234, None);
+        w.begin(SECTION_LOG);
+        let _ = w.write_str("[    1.000] info  mm: heap ready\n");
+        w.finish(1500, 2)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut buf = [0u8; 1024];
+        let len = sample(&mut buf);
+        let header = validate(&buf[..len]).unwrap();
+        assert_eq!(header.total_len(), len);
+        assert_eq!(header.uptime_ms, 1500);
+        assert_eq!(header.cpu, 2);
+        assert_eq!(message(&buf).unwrap(), "panicked at mm/heap.rs:42");
+
+        let kinds: alloc::vec::Vec<u16> = sections(&buf).map(|(kind, _)| kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SECTION_MESSAGE,
+                SECTION_REGISTERS,
+                SECTION_BACKTRACE,
+                SECTION_LOG
+            ]
+        );
+        let (_, regs) = sections(&buf).nth(1).unwrap();
+        assert_eq!(regs.len(), 32);
+        assert_eq!(&regs[..3], b"rip");
+        let (_, frames) = sections(&buf).nth(2).unwrap();
+        assert_eq!(frames.len(), 16 + 26 + 16);
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let mut buf = [0u8; 1024];
+        let len = sample(&mut buf);
+        buf[HEADER_LEN + 10] ^= 0xFF;
+        assert!(validate(&buf[..len]).is_none());
+    }
+
+    #[test]
+    fn test_seen_flag_outside_crc() {
+        let mut buf = [0u8; 1024];
+        let len = sample(&mut buf);
+        set_seen(&mut buf, true);
+        assert_eq!(validate(&buf[..len]).unwrap().flags, FLAG_SEEN);
+    }
+
+    #[test]
+    fn test_truncation_keeps_dump_valid() {
+        let mut buf = [0u8; 96];
+        let mut w = DumpWriter::new(&mut buf);
+        w.begin(SECTION_LOG);
+        for _ in 0..10 {
+            let _ = w.write_str("a long log line that will not fit\n");
+        }
+        let len = w.finish(0, 0);
+        assert_eq!(len, 96);
+        let (kind, data) = sections(&buf).next().unwrap();
+        assert_eq!(kind, SECTION_LOG);
+        assert_eq!(data.len(), 96 - HEADER_LEN - 8);
+        assert!(validate(&buf).is_some());
+    }
+}
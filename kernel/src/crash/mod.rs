@@ -0,0 +1,313 @@
+//! Kernel crash dumps
+//!
+//! When the kernel panics, [`on_panic`] records a crash dump
+//! ([`dump`] describes the format) before the architecture's panic handler
+//! prints the message and halts. The dump holds:
+//!
+//! - the panic message;
+//! - the CPU registers, plus the interrupted context when the panic comes from
+//!   a CPU exception handler (see [`set_exception`]);
+//! - a frame-pointer backtrace ([`backtrace`]), symbolized from the table
+//!   embedded in the kernel image ([`symbols`]);
+//! - the last [`LOG_LINES`] kernel log lines.
+//!
+//! The dump is written to the [`CRASH_PARTITION`] GPT partition of the
+//! virtio-blk disk, if there is one; the `bootimage-builder --ab` disk has
+//! it. On the next boot [`init`] reads it back and warns about it until user
+//! space acknowledges it. `SYS_CRASH_DUMP` returns the dump; the `crashdump`
+//! tool pretty-prints and extracts it.
+//!
+//! The panic path does not use the heap and never waits for a lock: the
+//! dump is built in a static buffer, log buffers and the disk are skipped if
+//! busy. The virtio-blk driver still needs a DMA frame per request, so a
+//! panic while the frame allocator is locked loses the on-disk copy.
+
+pub mod backtrace;
+pub mod dump;
+pub mod symbols;
+
+use alloc::{vec, vec::Vec};
+use core::{
+    cell::UnsafeCell,
+    fmt::Write,
+    panic::PanicInfo,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use spin::Mutex;
+
+use self::{backtrace::Registers, dump::DumpWriter, symbols::Symbol};
+use crate::{
+    bootslot::{self, Extent},
+    drivers::virtio::blk::{self, BlockDevice, BLOCK_SIZE},
+    error::KernelError,
+    klog,
+};
+
+/// GPT partition name of the crash dump area.
+pub const CRASH_PARTITION: &str = "veridian-crash";
+
+/// Largest dump recorded.
+pub const MAX_DUMP: usize = 64 * 1024;
+
+/// Kernel log lines kept in a dump.
+pub const LOG_LINES: usize = 64;
+
+/// CPU state at a fatal exception, as saved by the exception entry.
+#[derive(Debug, Clone, Copy)]
+pub struct ExceptionFrame {
+    pub ip: u64,
+    pub sp: u64,
+    /// Frame pointer of the interrupted function, 0 if unknown.
+    pub fp: u64,
+    pub flags: u64,
+    pub error_code: u64,
+    /// Faulting address for page faults, 0 otherwise.
+    pub fault_addr: u64,
+}
+
+/// Buffer the dump is assembled in. Only the CPU that set [`PANICKING`]
+/// touches it.
+struct DumpArea(UnsafeCell<[u8; MAX_DUMP]>);
+
+// SAFETY: Access is serialized by PANICKING, see `on_panic`.
+unsafe impl Sync for DumpArea {}
+
+static DUMP_AREA: DumpArea = DumpArea(UnsafeCell::new([0; MAX_DUMP]));
+
+/// Set by the first CPU to panic; later panics skip the dump.
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Context of the exception that is about to panic, if any.
+static EXCEPTION: Mutex<Option<ExceptionFrame>> = Mutex::new(None);
+
+/// The crash partition, once found by [`init`].
+static PARTITION: Mutex<Option<Extent>> = Mutex::new(None);
+
+/// The dump left by the previous boot.
+static PREVIOUS: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+/// Record the interrupted context of a fatal exception. Called by exception
+/// handlers right before they panic, so the dump shows where the fault
+/// happened rather than only the handler's own frames.
+pub fn set_exception(frame: ExceptionFrame) {
+    if let Some(mut slot) = EXCEPTION.try_lock() {
+        *slot = Some(frame);
+    }
+}
+
+fn with_device<R>(
+    f: impl FnOnce(&mut dyn BlockDevice) -> Result<R, KernelError>,
+) -> Result<R, KernelError> {
+    let device = blk::get_device().ok_or(KernelError::NotInitialized {
+        subsystem: "virtio-blk",
+    })?;
+    let mut dev = device.lock();
+    f(&mut *dev)
+}
+
+/// Read the dump stored at `extent`, if there is a valid one.
+fn read_dump(dev: &mut dyn BlockDevice, extent: Extent) -> Result<Option<Vec<u8>>, KernelError> {
+    let mut sector = [0u8; BLOCK_SIZE];
+    dev.read_block(extent.first_lba, &mut sector)?;
+    let Some(header) = dump::DumpHeader::decode(&sector) else {
+        return Ok(None);
+    };
+    let len = header.total_len();
+    if len > MAX_DUMP || len as u64 > extent.sectors * BLOCK_SIZE as u64 {
+        return Ok(None);
+    }
+    let mut data = vec![0u8; len.next_multiple_of(BLOCK_SIZE)];
+    for (i, chunk) in data.chunks_mut(BLOCK_SIZE).enumerate() {
+        dev.read_block(extent.first_lba + i as u64, chunk)?;
+    }
+    data.truncate(len);
+    Ok(dump::validate(&data).map(|_| data))
+}
+
+/// Find the crash partition and pick up the dump of a crashed previous
+/// boot. Runs after the virtio-blk driver is up.
+pub fn init() {
+    let Some(device) = blk::get_device() else {
+        return;
+    };
+    let mut dev = device.lock();
+    let Some(extent) = bootslot::find_partition(&mut *dev, CRASH_PARTITION) else {
+        return;
+    };
+    *PARTITION.lock() = Some(extent);
+
+    match read_dump(&mut *dev, extent) {
+        Ok(Some(data)) => {
+            let header = dump::validate(&data).expect("validated by read_dump");
+            if header.flags & dump::FLAG_SEEN == 0 {
+                klog!(
+                    Warn,
+                    "crash",
+                    "previous boot panicked: {}; run `crashdump` for details",
+                    dump::message(&data).unwrap_or("(no message)")
+                );
+            }
+            *PREVIOUS.lock() = Some(data);
+        }
+        Ok(None) => {}
+        Err(e) => klog!(Warn, "crash", "cannot read crash partition: {:?}", e),
+    }
+}
+
+/// The dump left by the previous boot.
+pub fn previous() -> Option<Vec<u8>> {
+    PREVIOUS.lock().clone()
+}
+
+/// Mark the previous boot's dump as seen, so later boots stop warning
+/// about it.
+pub fn acknowledge() -> Result<(), KernelError> {
+    let mut previous = PREVIOUS.lock();
+    let data = previous.as_mut().ok_or(KernelError::NotFound {
+        resource: "crash dump",
+        id: 0,
+    })?;
+    let extent = PARTITION.lock().ok_or(KernelError::NotFound {
+        resource: "crash partition",
+        id: 0,
+    })?;
+    dump::set_seen(data, true);
+    let mut sector = [0u8; BLOCK_SIZE];
+    let n = data.len().min(BLOCK_SIZE);
+    sector[..n].copy_from_slice(&data[..n]);
+    with_device(|dev| dev.write_block(extent.first_lba, &sector))
+}
+
+/// Write `data` to the crash partition without waiting for any lock.
+fn save(data: &[u8]) -> Result<(), KernelError> {
+    let partition = *PARTITION.try_lock().ok_or(KernelError::WouldBlock)?;
+    let extent = partition.ok_or(KernelError::NotFound {
+        resource: "crash partition",
+        id: 0,
+    })?;
+    if data.len() as u64 > extent.sectors * BLOCK_SIZE as u64 {
+        return Err(KernelError::ResourceExhausted {
+            resource: "crash partition space",
+        });
+    }
+    let mut dev = blk::get_device()
+        .ok_or(KernelError::NotInitialized {
+            subsystem: "virtio-blk",
+        })?
+        .try_lock()
+        .ok_or(KernelError::WouldBlock)?;
+    // The header sector goes last, behind a write barrier, so a dump cut
+    // short by a reset is never mistaken for a complete one.
+    let mut sector = [0u8; BLOCK_SIZE];
+    for (i, chunk) in data.chunks(BLOCK_SIZE).enumerate().rev() {
+        sector.fill(0);
+        sector[..chunk.len()].copy_from_slice(chunk);
+        if i == 0 {
+            dev.barrier()?;
+            dev.write_block_fua(extent.first_lba, &sector)?;
+        } else {
+            dev.write_block(extent.first_lba + i as u64, &sector)?;
+        }
+    }
+    Ok(())
+}
+
+/// Symbolize a return address: the call instruction is the byte before it.
+pub fn symbolize(ret: u64) -> Option<Symbol> {
+    symbols::lookup(ret.wrapping_sub(1)).map(|mut symbol| {
+        symbol.offset += 1;
+        symbol
+    })
+}
+
+fn name_offset(symbol: &Option<Symbol>) -> Option<(&str, u64)> {
+    symbol.as_ref().map(|s| (s.name(), s.offset))
+}
+
+/// Walk the stack the crash happened on: from the interrupted function for
+/// an exception, so the handler's own frames are skipped, else from here.
+fn walk_crash_stack(regs: &Registers, exception: Option<ExceptionFrame>, f: impl FnMut(u64)) {
+    match exception {
+        Some(exc) if exc.fp != 0 => backtrace::walk(exc.fp, exc.sp, f),
+        _ => backtrace::walk(regs.frame_pointer(), regs.stack_pointer(), f),
+    };
+}
+
+/// Record a crash dump for `info` and print its backtrace. Called by the
+/// panic handler before the architecture's own panic output.
+#[inline(never)]
+pub fn on_panic(info: &PanicInfo) {
+    if PANICKING.swap(true, Ordering::SeqCst) {
+        // A panic while dumping; the first dump is what matters.
+        return;
+    }
+    let regs = backtrace::capture();
+    let exception = EXCEPTION.try_lock().and_then(|slot| *slot);
+
+    // SAFETY: PANICKING guarantees this is the only reference.
+    let buf = unsafe { &mut *DUMP_AREA.0.get() };
+    let mut w = DumpWriter::new(&mut buf[..]);
+
+    w.begin(dump::SECTION_MESSAGE);
+    let _ = write!(w, "{}", info);
+
+    w.begin(dump::SECTION_REGISTERS);
+    if let Some(exc) = exception {
+        w.register("exc.ip", exc.ip);
+        w.register("exc.sp", exc.sp);
+        w.register("exc.fp", exc.fp);
+        w.register("exc.flag", exc.flags);
+        w.register("exc.err", exc.error_code);
+        w.register("exc.addr", exc.fault_addr);
+    }
+    for (name, value) in regs.iter() {
+        w.register(name, value);
+    }
+
+    w.begin(dump::SECTION_BACKTRACE);
+    if let Some(exc) = exception {
+        w.frame(exc.ip, name_offset(&symbols::lookup(exc.ip)));
+    }
+    walk_crash_stack(&regs, exception, |ret| {
+        w.frame(ret, name_offset(&symbolize(ret)))
+    });
+
+    w.begin(dump::SECTION_LOG);
+    crate::log_service::log_tail(LOG_LINES, &mut w);
+
+    let len = w.finish(
+        crate::arch::timer::get_timestamp_ms(),
+        crate::sched::smp::current_cpu_id() as u32,
+    );
+
+    let saved = save(&buf[..len]);
+
+    crate::println!("[CRASH] backtrace:");
+    let mut index = 0;
+    let mut print_frame = |addr: u64, symbol: Option<(&str, u64)>| {
+        match symbol {
+            Some((name, offset)) => {
+                crate::println!(
+                    "[CRASH]   #{:<2} {:#018x} {}+{:#x}",
+                    index,
+                    addr,
+                    name,
+                    offset
+                )
+            }
+            None => crate::println!("[CRASH]   #{:<2} {:#018x} ?", index, addr),
+        }
+        index += 1;
+    };
+    if let Some(exc) = exception {
+        print_frame(exc.ip, name_offset(&symbols::lookup(exc.ip)));
+    }
+    walk_crash_stack(&regs, exception, |ret| {
+        print_frame(ret, name_offset(&symbolize(ret)))
+    });
+    match saved {
+        Ok(()) => crate::println!("[CRASH] dump saved to {} ({} bytes)", CRASH_PARTITION, len),
+        Err(reason) => crate::println!("[CRASH] dump not saved: {}", reason),
+    }
+}
@@ -0,0 +1,322 @@
+//! Embedded kernel symbol table
+//!
+//! The kernel carries an empty, fixed-size table in its own `.ksymtab`
+//! section. After linking, `tools/gen-symtab` fills it in place with the
+//! kernel's function symbols, so a panic can name the frames of its
+//! backtrace without any help from the host. A kernel that was not patched
+//! simply has no symbols.
+//!
+//! Table layout (little-endian):
+//!
+//! | Offset | Size | Field                                                |
+//! |--------|------|------------------------------------------------------|
+//! | 0      | 4    | magic `KSYM`                                         |
+//! | 4      | 4    | format version ([`KSYMTAB_VERSION`])                 |
+//! | 8      | 4    | number of symbols *n*                                |
+//! | 12     | 4    | offset of the block index                            |
+//! | 16     | 4    | offset of the names                                  |
+//! | 20     | 4    | reserved                                             |
+//! | 24     | 8    | link-time address of the table itself                |
+//! | 32     | 8    | base address of the entries                          |
+//! | 40     | 8*n  | `{ addr - base: u32, size: u32 }`, sorted by address |
+//!
+//! Names are demangled, stripped of their hash and front-coded in blocks of
+//! [`BLOCK`]: each is `{ shared: u8, len: u8 }` followed by the `len` bytes
+//! that come after the first `shared` bytes of the previous name. The first
+//! name of a block shares nothing, and the block index holds one `u32`
+//! offset per block into the names. The table's link-time address lets the
+//! lookup correct for a kernel that runs somewhere other than where it was
+//! linked.
+
+/// Size reserved for the table, names included.
+pub const KSYMTAB_SIZE: usize = 1 << 20;
+
+pub const KSYMTAB_MAGIC: &[u8; 4] = b"KSYM";
+pub const KSYMTAB_VERSION: u32 = 2;
+
+/// Names per front-coded block.
+pub const BLOCK: usize = 16;
+
+/// Longest symbol name stored.
+pub const MAX_NAME: usize = 255;
+
+const HEADER_LEN: usize = 40;
+const ENTRY_LEN: usize = 8;
+
+const fn empty_table() -> [u8; KSYMTAB_SIZE] {
+    let mut table = [0u8; KSYMTAB_SIZE];
+    table[0] = KSYMTAB_MAGIC[0];
+    table[1] = KSYMTAB_MAGIC[1];
+    table[2] = KSYMTAB_MAGIC[2];
+    table[3] = KSYMTAB_MAGIC[3];
+    table[4] = KSYMTAB_VERSION as u8;
+    table
+}
+
+/// The table patched by `tools/gen-symtab`, found by section name.
+#[no_mangle]
+#[used]
+#[cfg_attr(target_os = "none", link_section = ".ksymtab")]
+pub static VERIDIAN_KSYMTAB: [u8; KSYMTAB_SIZE] = empty_table();
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn read_u64(buf: &[u8], off: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[off..off + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// A symbolized address: the containing function and the offset into it.
+///
+/// Names are decoded into the symbol itself, so a lookup needs no heap.
+pub struct Symbol {
+    name: [u8; MAX_NAME],
+    len: usize,
+    pub offset: u64,
+}
+
+impl Symbol {
+    pub fn name(&self) -> &str {
+        // A name cut at MAX_NAME may end inside a character.
+        match core::str::from_utf8(&self.name[..self.len]) {
+            Ok(name) => name,
+            Err(e) => core::str::from_utf8(&self.name[..e.valid_up_to()]).unwrap_or("?"),
+        }
+    }
+}
+
+/// A parsed view of a symbol table.
+pub struct SymbolTable<'a> {
+    table: &'a [u8],
+    count: usize,
+    index: usize,
+    names: usize,
+    base: u64,
+    /// Runtime minus link-time address of the kernel.
+    slide: u64,
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Parse `table`, which is loaded at `runtime_addr`. Returns `None` for
+    /// a malformed or unpatched table.
+    pub fn parse(table: &'a [u8], runtime_addr: u64) -> Option<Self> {
+        if table.len() < HEADER_LEN
+            || &table[0..4] != KSYMTAB_MAGIC
+            || read_u32(table, 4) != KSYMTAB_VERSION
+        {
+            return None;
+        }
+        let count = read_u32(table, 8) as usize;
+        let index = read_u32(table, 12) as usize;
+        let names = read_u32(table, 16) as usize;
+        if count == 0
+            || HEADER_LEN + count * ENTRY_LEN > index
+            || index + count.div_ceil(BLOCK) * 4 > names
+            || names > table.len()
+        {
+            return None;
+        }
+        Some(Self {
+            table,
+            count,
+            index,
+            names,
+            base: read_u64(table, 32),
+            slide: runtime_addr.wrapping_sub(read_u64(table, 24)),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Link-time address and size of entry `index`.
+    fn entry(&self, index: usize) -> (u64, u32) {
+        let off = HEADER_LEN + index * ENTRY_LEN;
+        (
+            self.base + read_u32(self.table, off) as u64,
+            read_u32(self.table, off + 4),
+        )
+    }
+
+    /// Decode the name of entry `index` into `out`, returning its length.
+    /// A corrupt name decodes as empty.
+    fn name(&self, index: usize, out: &mut [u8; MAX_NAME]) -> usize {
+        let block = index / BLOCK;
+        let mut pos = self.names + read_u32(self.table, self.index + block * 4) as usize;
+        let mut len = 0;
+        for _ in block * BLOCK..=index {
+            let (Some(&shared), Some(&suffix)) = (self.table.get(pos), self.table.get(pos + 1))
+            else {
+                return 0;
+            };
+            let (shared, suffix) = (shared as usize, suffix as usize);
+            let Some(bytes) = self.table.get(pos + 2..pos + 2 + suffix) else {
+                return 0;
+            };
+            if shared > len || shared + suffix > MAX_NAME {
+                return 0;
+            }
+            out[shared..shared + suffix].copy_from_slice(bytes);
+            len = shared + suffix;
+            pos += 2 + suffix;
+        }
+        len
+    }
+
+    /// The symbol containing runtime address `addr`.
+    ///
+    /// A symbol without a recorded size is taken to extend to the next one.
+    pub fn lookup(&self, addr: u64) -> Option<Symbol> {
+        let addr = addr.wrapping_sub(self.slide);
+        // Index of the first symbol above `addr`.
+        let (mut lo, mut hi) = (0, self.count);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.entry(mid).0 <= addr {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let index = lo.checked_sub(1)?;
+        let (start, size) = self.entry(index);
+        let offset = addr - start;
+        if size != 0 && offset >= size as u64 {
+            return None;
+        }
+        let mut symbol = Symbol {
+            name: [0; MAX_NAME],
+            len: 0,
+            offset,
+        };
+        symbol.len = self.name(index, &mut symbol.name);
+        Some(symbol)
+    }
+}
+
+/// The kernel's own symbol table, if it was patched in.
+pub fn kernel() -> Option<SymbolTable<'static>> {
+    // The table is patched after compilation, so its contents must not be
+    // constant-folded from the placeholder.
+    let table: &'static [u8; KSYMTAB_SIZE] = core::hint::black_box(&VERIDIAN_KSYMTAB);
+    SymbolTable::parse(table, table.as_ptr() as u64)
+}
+
+/// Symbolize a runtime address against the kernel's table.
+pub fn lookup(addr: u64) -> Option<Symbol> {
+    kernel()?.lookup(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::String, vec::Vec};
+
+    use super::*;
+
+    /// Build a table linked at `anchor` holding `(addr, size, name)`, the
+    /// way `tools/gen-symtab` does.
+    fn build(anchor: u64, symbols: &[(u64, u32, &str)]) -> Vec<u8> {
+        let base = symbols[0].0;
+        let index_off = HEADER_LEN + symbols.len() * ENTRY_LEN;
+        let names_off = index_off + symbols.len().div_ceil(BLOCK) * 4;
+        let mut index = Vec::new();
+        let mut names = Vec::new();
+        for (i, &(_, _, name)) in symbols.iter().enumerate() {
+            let shared = if i % BLOCK == 0 {
+                index.extend_from_slice(&(names.len() as u32).to_le_bytes());
+                0
+            } else {
+                let prev = symbols[i - 1].2.as_bytes();
+                prev.iter()
+                    .zip(name.as_bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            };
+            names.push(shared as u8);
+            names.push((name.len() - shared) as u8);
+            names.extend_from_slice(&name.as_bytes()[shared..]);
+        }
+
+        let mut table = Vec::new();
+        table.extend_from_slice(KSYMTAB_MAGIC);
+        table.extend_from_slice(&KSYMTAB_VERSION.to_le_bytes());
+        table.extend_from_slice(&(symbols.len() as u32).to_le_bytes());
+        table.extend_from_slice(&(index_off as u32).to_le_bytes());
+        table.extend_from_slice(&(names_off as u32).to_le_bytes());
+        table.extend_from_slice(&0u32.to_le_bytes());
+        table.extend_from_slice(&anchor.to_le_bytes());
+        table.extend_from_slice(&base.to_le_bytes());
+        for &(addr, size, _) in symbols {
+            table.extend_from_slice(&((addr - base) as u32).to_le_bytes());
+            table.extend_from_slice(&size.to_le_bytes());
+        }
+        table.extend_from_slice(&index);
+        table.extend_from_slice(&names);
+        table
+    }
+
+    fn resolve(symbols: &SymbolTable, addr: u64) -> Option<(String, u64)> {
+        symbols
+            .lookup(addr)
+            .map(|s| (String::from(s.name()), s.offset))
+    }
+
+    #[test]
+    fn test_lookup() {
+        let table = build(
+            0x9000,
+            &[
+                (0x1000, 0x20, "kmain"),
+                (0x1100, 0, "panic"),
+                (0x2000, 0x10, "idle"),
+            ],
+        );
+        let symbols = SymbolTable::parse(&table, 0x9000).unwrap();
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(resolve(&symbols, 0x1000), Some(("kmain".into(), 0)));
+        assert_eq!(resolve(&symbols, 0x101f), Some(("kmain".into(), 0x1f)));
+        assert_eq!(resolve(&symbols, 0x1020), None);
+        assert_eq!(resolve(&symbols, 0x1800), Some(("panic".into(), 0x700)));
+        assert_eq!(resolve(&symbols, 0x2004), Some(("idle".into(), 4)));
+        assert_eq!(resolve(&symbols, 0x0fff), None);
+    }
+
+    #[test]
+    fn test_front_coded_names() {
+        let names: Vec<String> = (0..40)
+            .map(|i| format!("veridian_kernel::mm::frame{}", i))
+            .collect();
+        let symbols: Vec<(u64, u32, &str)> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (0x1000 + i as u64 * 0x10, 0x10, name.as_str()))
+            .collect();
+        let table = build(0, &symbols);
+        let symbols = SymbolTable::parse(&table, 0).unwrap();
+        for (i, name) in names.iter().enumerate() {
+            let addr = 0x1000 + i as u64 * 0x10 + 3;
+            assert_eq!(resolve(&symbols, addr), Some((name.clone(), 3)));
+        }
+    }
+
+    #[test]
+    fn test_lookup_applies_slide() {
+        let table = build(0x9000, &[(0x1000, 0x20, "kmain")]);
+        let symbols = SymbolTable::parse(&table, 0x10_9000).unwrap();
+        assert_eq!(resolve(&symbols, 0x10_1008), Some(("kmain".into(), 8)));
+        assert_eq!(resolve(&symbols, 0x1008), None);
+    }
+
+    #[test]
+    fn test_unpatched_table() {
+        assert!(SymbolTable::parse(&VERIDIAN_KSYMTAB, 0).is_none());
+    }
+}
@@ -105,14 +105,14 @@ impl Fe {
         s[21] = (h.0[3] >> 15) as u8;
         s[22] = (h.0[3] >> 23) as u8;
         s[23] = (h.0[3] >> 31) as u8;
-        s[24] = ((h.0[3] >> 39) | (h.0[4] << 12)) as u8;
-        s[25] = (h.0[4] >> 4) as u8;
-        s[26] = (h.0[4] >> 12) as u8;
-        s[27] = (h.0[4] >> 20) as u8;
-        s[28] = (h.0[4] >> 28) as u8;
-        s[29] = (h.0[4] >> 36) as u8;
-        s[30] = (h.0[4] >> 44) as u8;
-        s[31] = 0; // top bit always 0 for reduced element
+        s[24] = (h.0[3] >> 39) as u8;
+        s[25] = ((h.0[3] >> 47) | (h.0[4] << 4)) as u8;
+        s[26] = (h.0[4] >> 4) as u8;
+        s[27] = (h.0[4] >> 12) as u8;
+        s[28] = (h.0[4] >> 20) as u8;
+        s[29] = (h.0[4] >> 28) as u8;
+        s[30] = (h.0[4] >> 36) as u8;
+        s[31] = (h.0[4] >> 44) as u8; // top bit always 0 for reduced element
 
         s
     }
@@ -151,23 +151,23 @@ impl Fe {
 
     /// Subtraction in GF(p)
     fn sub(&self, other: &Fe) -> Fe {
-        // Add 2*p to avoid underflow (each limb gets 2 * (2^51 - 1) headroom,
-        // except limb 0 which gets 2*(2^51 - 19))
+        // Add 16*p to avoid underflow: operands may be unreduced sums whose
+        // limbs exceed 2*(2^51 - 1)
         let mut r = Fe::ZERO;
         r.0[0] = self.0[0]
-            .wrapping_add(0xfffffffffffda) // 2*(2^51 - 19)
+            .wrapping_add(0x7ffffffffffed0) // 16*(2^51 - 19)
             .wrapping_sub(other.0[0]);
         r.0[1] = self.0[1]
-            .wrapping_add(0xffffffffffffe) // 2*(2^51 - 1)
+            .wrapping_add(0x7ffffffffffff0) // 16*(2^51 - 1)
             .wrapping_sub(other.0[1]);
         r.0[2] = self.0[2]
-            .wrapping_add(0xffffffffffffe)
+            .wrapping_add(0x7ffffffffffff0)
             .wrapping_sub(other.0[2]);
         r.0[3] = self.0[3]
-            .wrapping_add(0xffffffffffffe)
+            .wrapping_add(0x7ffffffffffff0)
             .wrapping_sub(other.0[3]);
         r.0[4] = self.0[4]
-            .wrapping_add(0xffffffffffffe)
+            .wrapping_add(0x7ffffffffffff0)
             .wrapping_sub(other.0[4]);
         r.reduce();
         r
@@ -406,12 +406,6 @@ impl EdPoint {
         Fe::from_bytes(&d_bytes)
     }
 
-    /// 2*d
-    fn curve_2d() -> Fe {
-        let d = Self::curve_d();
-        d.add(&d)
-    }
-
     /// Encode point to 32 bytes (compressed Edwards point)
     fn encode(&self) -> [u8; 32] {
         let zi = self.z.invert();
@@ -518,12 +512,11 @@ impl EdPoint {
 
     /// Point addition in extended coordinates
     fn add(&self, other: &EdPoint) -> EdPoint {
-        let d2 = Self::curve_2d();
-
+        // add-2008-hwcd with a = -1
         let a = self.x.mul(&other.x);
         let b = self.y.mul(&other.y);
-        let c = self.t.mul(&d2).mul(&other.t);
-        let d = self.z.mul(&other.z).add(&self.z.mul(&other.z)); // 2*Z1*Z2
+        let c = self.t.mul(&Self::curve_d()).mul(&other.t);
+        let d = self.z.mul(&other.z);
 
         let e = self
             .x
@@ -759,10 +752,10 @@ fn sc_muladd_reduce(s: &mut [i64; 24]) {
     s[11] += s[18] * L5;
     s[18] = 0;
 
-    // Carry
+    // Carry (s[17] is folded next, so the last carry goes into it)
     let mut carry: i64;
     let mut i = 6;
-    while i < 18 {
+    while i < 17 {
         carry = (s[i] + (1 << 20)) >> 21;
         s[i + 1] += carry;
         s[i] -= carry << 21;
@@ -827,7 +820,7 @@ fn sc_muladd_reduce(s: &mut [i64; 24]) {
         i += 1;
     }
 
-    // One more reduction pass if needed
+    // Two more reduction passes: the first can still carry into s[12]
     s[0] += s[12] * L0;
     s[1] += s[12] * L1;
     s[2] += s[12] * L2;
@@ -843,6 +836,22 @@ fn sc_muladd_reduce(s: &mut [i64; 24]) {
         s[i] -= carry << 21;
         i += 1;
     }
+
+    s[0] += s[12] * L0;
+    s[1] += s[12] * L1;
+    s[2] += s[12] * L2;
+    s[3] += s[12] * L3;
+    s[4] += s[12] * L4;
+    s[5] += s[12] * L5;
+    s[12] = 0;
+
+    i = 0;
+    while i < 11 {
+        carry = s[i] >> 21;
+        s[i + 1] += carry;
+        s[i] -= carry << 21;
+        i += 1;
+    }
 }
 
 // ============================================================================
@@ -1203,6 +1212,14 @@ fn sc_muladd(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
     s[22] = a11 * b11;
     s[23] = 0;
 
+    // Bring every limb back to 21 bits first, or the products in the
+    // reduction overflow i64
+    for i in 0..23 {
+        let carry = (s[i] + (1 << 20)) >> 21;
+        s[i + 1] += carry;
+        s[i] -= carry << 21;
+    }
+
     // Reduce mod L
     sc_muladd_reduce(&mut s);
 
@@ -1270,7 +1287,7 @@ pub(crate) fn x25519_scalar_mult(scalar: &[u8; 32], u_point: &[u8; 32]) -> [u8;
     let mut z_3 = Fe::ONE;
     let mut swap: u64 = 0;
 
-    let a24 = Fe([121666, 0, 0, 0, 0]); // (A+2)/4 where A=486662
+    let a24 = Fe([121665, 0, 0, 0, 0]); // (A-2)/4 where A=486662 (RFC 7748)
 
     let mut t: i32 = 254;
     while t >= 0 {
@@ -1392,15 +1409,64 @@ pub mod key_exchange {
     }
 }
 
-// Asymmetric crypto tests require bare-metal execution: the custom
-// Ed25519/X25519 scalar reduction uses i64 arithmetic that can overflow in
-// debug builds on non-reduced random inputs, and the results are only
-// guaranteed correct with properly bounded scalar inputs produced by the kernel
-// PRNG.
-#[cfg(all(test, target_os = "none"))]
+// Key generation needs the kernel PRNG, so those tests only run bare-metal.
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// RFC 8032 section 7.1, test 2
+    #[test]
+    fn test_ed25519_rfc8032_vector() {
+        let seed: [u8; 32] =
+            hex("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb")
+                .try_into()
+                .unwrap();
+        let keypair = KeyPair::from_seed(&seed).unwrap();
+        assert_eq!(
+            keypair.verifying_key.as_bytes()[..],
+            hex("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c")[..]
+        );
+
+        let signature = keypair.sign(&[0x72]).unwrap();
+        assert_eq!(
+            signature.as_bytes()[..],
+            hex(concat!(
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+                "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+            ))[..]
+        );
+    }
+
+    /// RFC 7748 section 5.2, first vector
+    #[test]
+    fn test_x25519_rfc7748_vector() {
+        let scalar = hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let u = hex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+        let out = x25519_scalar_mult(&scalar.try_into().unwrap(), &u.try_into().unwrap());
+        assert_eq!(
+            out[..],
+            hex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")[..]
+        );
+    }
+
+    #[test]
+    fn test_sign_verify_from_seed() {
+        let keypair = KeyPair::from_seed(&[0x4c; 32]).unwrap();
+        let message = b"Hello, VeridianOS!";
+
+        let signature = keypair.sign(message).unwrap();
+        assert!(keypair.verify(message, &signature).unwrap());
+        assert!(!keypair.verify(b"Hello, VeridianOS?", &signature).unwrap());
+    }
+
+    #[cfg(target_os = "none")]
     #[test]
     fn test_keypair_generation() {
         let keypair = KeyPair::generate().unwrap();
@@ -1412,6 +1478,7 @@ mod tests {
         assert!(verified);
     }
 
+    #[cfg(target_os = "none")]
     #[test]
     fn test_key_exchange() {
         use key_exchange::*;
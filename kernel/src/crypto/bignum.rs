@@ -0,0 +1,346 @@
+//! Big Integer Arithmetic for Signature Verification
+//!
+//! Unsigned multi-precision integers as little-endian `u64` limbs, with
+//! Montgomery multiplication modulo an odd modulus. This backs RSA and ECDSA
+//! *verification* only: every operation is variable-time, which is fine for
+//! public keys, signatures and curve points but must never see a secret.
+
+#![allow(dead_code, clippy::wrong_self_convention)]
+
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+/// Little-endian limbs of `bytes` (big-endian), at least `min_limbs` long
+pub(crate) fn from_be_bytes(bytes: &[u8], min_limbs: usize) -> Vec<u64> {
+    let mut limbs = vec![0u64; bytes.len().div_ceil(8).max(min_limbs)];
+    for (i, &b) in bytes.iter().rev().enumerate() {
+        limbs[i / 8] |= (b as u64) << ((i % 8) * 8);
+    }
+    limbs
+}
+
+/// Big-endian encoding of `limbs`, left-padded or truncated to `len` bytes
+pub(crate) fn to_be_bytes(limbs: &[u64], len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    for (i, byte) in out.iter_mut().rev().enumerate() {
+        if let Some(limb) = limbs.get(i / 8) {
+            *byte = (limb >> ((i % 8) * 8)) as u8;
+        }
+    }
+    out
+}
+
+/// Compare two numbers of possibly different limb counts
+pub(crate) fn cmp(a: &[u64], b: &[u64]) -> Ordering {
+    let len = a.len().max(b.len());
+    for i in (0..len).rev() {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        match x.cmp(&y) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    Ordering::Equal
+}
+
+/// Is `a` zero?
+pub(crate) fn is_zero(a: &[u64]) -> bool {
+    a.iter().all(|&l| l == 0)
+}
+
+/// Number of significant bits in `a`
+pub(crate) fn bit_len(a: &[u64]) -> usize {
+    for i in (0..a.len()).rev() {
+        if a[i] != 0 {
+            return i * 64 + 64 - a[i].leading_zeros() as usize;
+        }
+    }
+    0
+}
+
+/// Bit `i` of `a`
+pub(crate) fn bit(a: &[u64], i: usize) -> bool {
+    a.get(i / 64).is_some_and(|l| (l >> (i % 64)) & 1 == 1)
+}
+
+/// `a += b` over `a.len()` limbs, returning the carry out
+fn add_assign(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = 0u64;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let (s1, c1) = limb.overflowing_add(b.get(i).copied().unwrap_or(0));
+        let (s2, c2) = s1.overflowing_add(carry);
+        *limb = s2;
+        carry = (c1 | c2) as u64;
+    }
+    carry != 0
+}
+
+/// `a -= b` over `a.len()` limbs, returning the borrow out
+fn sub_assign(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = 0u64;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let (d1, b1) = limb.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (d2, b2) = d1.overflowing_sub(borrow);
+        *limb = d2;
+        borrow = (b1 | b2) as u64;
+    }
+    borrow != 0
+}
+
+/// An odd modulus with precomputed Montgomery constants (R = 2^(64*limbs))
+pub(crate) struct Modulus {
+    m: Vec<u64>,
+    /// -m^-1 mod 2^64
+    m0_inv: u64,
+    /// R^2 mod m
+    r2: Vec<u64>,
+}
+
+impl Modulus {
+    /// Build from a big-endian modulus; `None` if it is even or below 3
+    pub(crate) fn new(m_be: &[u8]) -> Option<Self> {
+        let mut m = from_be_bytes(m_be, 1);
+        while m.len() > 1 && m[m.len() - 1] == 0 {
+            m.pop();
+        }
+        if m[0] & 1 == 0 || (m.len() == 1 && m[0] < 3) {
+            return None;
+        }
+
+        // Newton iteration doubles the correct low bits each round: 1, 2, 4 .. 64
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m[0].wrapping_mul(inv)));
+        }
+
+        // R^2 mod m by doubling 1 a total of 2 * 64 * limbs times
+        let n = m.len();
+        let mut r2 = vec![0u64; n];
+        r2[0] = 1;
+        for _ in 0..2 * 64 * n {
+            let doubled = r2.clone();
+            let carry = add_assign(&mut r2, &doubled);
+            if carry || cmp(&r2, &m) != Ordering::Less {
+                sub_assign(&mut r2, &m);
+            }
+        }
+
+        Some(Self {
+            m,
+            m0_inv: inv.wrapping_neg(),
+            r2,
+        })
+    }
+
+    /// Limb count of residues
+    pub(crate) fn limbs(&self) -> usize {
+        self.m.len()
+    }
+
+    /// Significant bits of the modulus
+    pub(crate) fn bits(&self) -> usize {
+        bit_len(&self.m)
+    }
+
+    /// The modulus itself
+    pub(crate) fn value(&self) -> &[u64] {
+        &self.m
+    }
+
+    /// Montgomery product a * b * R^-1 mod m (CIOS); requires a * b < m * R
+    #[allow(clippy::needless_range_loop)]
+    pub(crate) fn mont_mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = self.m.len();
+        let mut t = vec![0u64; n + 2];
+        for i in 0..n {
+            let bi = b.get(i).copied().unwrap_or(0) as u128;
+            let mut carry = 0u128;
+            for j in 0..n {
+                let aj = a.get(j).copied().unwrap_or(0) as u128;
+                let s = t[j] as u128 + aj * bi + carry;
+                t[j] = s as u64;
+                carry = s >> 64;
+            }
+            let s = t[n] as u128 + carry;
+            t[n] = s as u64;
+            t[n + 1] = (s >> 64) as u64;
+
+            let q = t[0].wrapping_mul(self.m0_inv) as u128;
+            let mut carry = (t[0] as u128 + q * self.m[0] as u128) >> 64;
+            for j in 1..n {
+                let s = t[j] as u128 + q * self.m[j] as u128 + carry;
+                t[j - 1] = s as u64;
+                carry = s >> 64;
+            }
+            let s = t[n] as u128 + carry;
+            t[n - 1] = s as u64;
+            t[n] = t[n + 1] + (s >> 64) as u64;
+        }
+
+        let high = t[n];
+        t.truncate(n);
+        if high != 0 || cmp(&t, &self.m) != Ordering::Less {
+            sub_assign(&mut t, &self.m);
+        }
+        t
+    }
+
+    /// Convert `a` (at most `limbs()` limbs, any value) into Montgomery form
+    pub(crate) fn to_mont(&self, a: &[u64]) -> Vec<u64> {
+        self.mont_mul(a, &self.r2)
+    }
+
+    /// Convert out of Montgomery form
+    pub(crate) fn from_mont(&self, a: &[u64]) -> Vec<u64> {
+        let mut one = vec![0u64; self.m.len()];
+        one[0] = 1;
+        self.mont_mul(a, &one)
+    }
+
+    /// `a mod m` for `a` of at most `limbs()` limbs
+    pub(crate) fn reduce(&self, a: &[u64]) -> Vec<u64> {
+        self.from_mont(&self.to_mont(a))
+    }
+
+    /// Montgomery form of 1
+    pub(crate) fn one(&self) -> Vec<u64> {
+        let mut one = vec![0u64; self.m.len()];
+        one[0] = 1;
+        self.to_mont(&one)
+    }
+
+    /// (a + b) mod m for reduced a, b (works in either domain)
+    pub(crate) fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut s = a.to_vec();
+        s.resize(self.m.len(), 0);
+        let carry = add_assign(&mut s, b);
+        if carry || cmp(&s, &self.m) != Ordering::Less {
+            sub_assign(&mut s, &self.m);
+        }
+        s
+    }
+
+    /// (a - b) mod m for reduced a, b (works in either domain)
+    pub(crate) fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut d = a.to_vec();
+        d.resize(self.m.len(), 0);
+        if sub_assign(&mut d, b) {
+            add_assign(&mut d, &self.m);
+        }
+        d
+    }
+
+    /// base^exp for `base` in Montgomery form; the result is too
+    pub(crate) fn pow(&self, base: &[u64], exp: &[u64]) -> Vec<u64> {
+        let mut acc = self.one();
+        for i in (0..bit_len(exp)).rev() {
+            acc = self.mont_mul(&acc, &acc);
+            if bit(exp, i) {
+                acc = self.mont_mul(&acc, base);
+            }
+        }
+        acc
+    }
+
+    /// Multiplicative inverse by Fermat's little theorem; the modulus must be
+    /// prime. Input and output are in Montgomery form.
+    pub(crate) fn inv_prime(&self, a: &[u64]) -> Vec<u64> {
+        let mut exp = self.m.clone();
+        sub_assign(&mut exp, &[2]);
+        self.pow(a, &exp)
+    }
+}
+
+/// Contents of the INTEGERs in a DER `SEQUENCE { INTEGER, INTEGER, ... }`,
+/// with leading zero bytes stripped. Negative integers are rejected.
+pub(crate) fn der_integer_sequence(der: &[u8]) -> Option<Vec<&[u8]>> {
+    let (tag, body, rest) = der_tlv(der)?;
+    if tag != 0x30 || !rest.is_empty() {
+        return None;
+    }
+    let mut ints = Vec::new();
+    let mut input = body;
+    while !input.is_empty() {
+        let (tag, mut value, rest) = der_tlv(input)?;
+        if tag != 0x02 || value.is_empty() || value[0] & 0x80 != 0 {
+            return None;
+        }
+        while value.len() > 1 && value[0] == 0 {
+            value = &value[1..];
+        }
+        ints.push(value);
+        input = rest;
+    }
+    Some(ints)
+}
+
+/// Split one DER TLV off the front of `input`: (tag, value, rest)
+fn der_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *input.first()?;
+    let first = *input.get(1)? as usize;
+    let (len, header): (usize, usize) = match first {
+        0..=0x7f => (first, 2),
+        0x81 => (*input.get(2)? as usize, 3),
+        0x82 => (
+            ((*input.get(2)? as usize) << 8) | *input.get(3)? as usize,
+            4,
+        ),
+        _ => return None,
+    };
+    let value = input.get(header..header.checked_add(len)?)?;
+    Some((tag, value, &input[header + len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_round_trip() {
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        let limbs = from_be_bytes(&bytes, 3);
+        assert_eq!(limbs, [0x0203040506070809, 0x01, 0]);
+        assert_eq!(to_be_bytes(&limbs, 9), bytes);
+        assert_eq!(to_be_bytes(&limbs, 10)[0], 0);
+        assert_eq!(bit_len(&limbs), 65);
+    }
+
+    #[test]
+    fn test_modular_arithmetic() {
+        // m = 2^127 - 1 (prime)
+        let m = Modulus::new(&[
+            0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff,
+        ])
+        .unwrap();
+        let a = m.to_mont(&[123456789, 0]);
+        let b = m.to_mont(&[987654321, 0]);
+        assert_eq!(
+            m.from_mont(&m.mont_mul(&a, &b)),
+            [123456789u64 * 987654321, 0]
+        );
+        assert_eq!(m.from_mont(&m.sub(&a, &b)), {
+            let mut v = m.value().to_vec();
+            sub_assign(&mut v, &[987654321 - 123456789]);
+            v
+        });
+
+        let inv = m.inv_prime(&a);
+        assert_eq!(m.from_mont(&m.mont_mul(&inv, &a)), [1, 0]);
+        assert_eq!(m.reduce(&[u64::MAX, u64::MAX]), [1, 0]);
+
+        assert!(Modulus::new(&[0x10]).is_none());
+    }
+
+    #[test]
+    fn test_der_integer_sequence() {
+        let der = [0x30, 0x08, 0x02, 0x02, 0x00, 0x80, 0x02, 0x02, 0x01, 0x00];
+        let ints = der_integer_sequence(&der).unwrap();
+        assert_eq!(ints, [&[0x80][..], &[0x01, 0x00][..]]);
+        // Negative INTEGER
+        assert!(der_integer_sequence(&[0x30, 0x03, 0x02, 0x01, 0x80]).is_none());
+        // Trailing garbage
+        assert!(der_integer_sequence(&[0x30, 0x00, 0x00]).is_none());
+    }
+}
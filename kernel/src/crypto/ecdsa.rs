@@ -0,0 +1,438 @@
+//! ECDSA Signature Verification
+//!
+//! Verifies ECDSA signatures (FIPS 186-4) over the NIST P-256 and P-384
+//! curves, the two curves used by Web PKI certificates and TLS 1.3
+//! `ecdsa_secp256r1_sha256` / `ecdsa_secp384r1_sha384`. Signing is not
+//! provided: the kernel only ever checks signatures made elsewhere, so the
+//! variable-time arithmetic in [`super::bignum`] is acceptable here.
+
+#![allow(dead_code)]
+
+use alloc::vec::Vec;
+
+use super::{
+    bignum::{self, Modulus},
+    CryptoError, CryptoResult,
+};
+
+/// Supported curves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Curve {
+    /// NIST P-256 (secp256r1)
+    P256,
+    /// NIST P-384 (secp384r1)
+    P384,
+}
+
+/// Short Weierstrass curve y^2 = x^3 - 3x + b (big-endian constants)
+struct CurveParams {
+    p: &'static [u8],
+    n: &'static [u8],
+    b: &'static [u8],
+    gx: &'static [u8],
+    gy: &'static [u8],
+}
+
+// FIPS 186-4 D.1.2.3
+const P256: CurveParams = CurveParams {
+    p: &[
+        0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff,
+    ],
+    n: &[
+        0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63,
+        0x25, 0x51,
+    ],
+    b: &[
+        0x5a, 0xc6, 0x35, 0xd8, 0xaa, 0x3a, 0x93, 0xe7, 0xb3, 0xeb, 0xbd, 0x55, 0x76, 0x98, 0x86,
+        0xbc, 0x65, 0x1d, 0x06, 0xb0, 0xcc, 0x53, 0xb0, 0xf6, 0x3b, 0xce, 0x3c, 0x3e, 0x27, 0xd2,
+        0x60, 0x4b,
+    ],
+    gx: &[
+        0x6b, 0x17, 0xd1, 0xf2, 0xe1, 0x2c, 0x42, 0x47, 0xf8, 0xbc, 0xe6, 0xe5, 0x63, 0xa4, 0x40,
+        0xf2, 0x77, 0x03, 0x7d, 0x81, 0x2d, 0xeb, 0x33, 0xa0, 0xf4, 0xa1, 0x39, 0x45, 0xd8, 0x98,
+        0xc2, 0x96,
+    ],
+    gy: &[
+        0x4f, 0xe3, 0x42, 0xe2, 0xfe, 0x1a, 0x7f, 0x9b, 0x8e, 0xe7, 0xeb, 0x4a, 0x7c, 0x0f, 0x9e,
+        0x16, 0x2b, 0xce, 0x33, 0x57, 0x6b, 0x31, 0x5e, 0xce, 0xcb, 0xb6, 0x40, 0x68, 0x37, 0xbf,
+        0x51, 0xf5,
+    ],
+};
+
+// FIPS 186-4 D.1.2.4
+const P384: CurveParams = CurveParams {
+    p: &[
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff,
+        0xff, 0xff, 0xff,
+    ],
+    n: &[
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xc7, 0x63, 0x4d, 0x81, 0xf4, 0x37,
+        0x2d, 0xdf, 0x58, 0x1a, 0x0d, 0xb2, 0x48, 0xb0, 0xa7, 0x7a, 0xec, 0xec, 0x19, 0x6a, 0xcc,
+        0xc5, 0x29, 0x73,
+    ],
+    b: &[
+        0xb3, 0x31, 0x2f, 0xa7, 0xe2, 0x3e, 0xe7, 0xe4, 0x98, 0x8e, 0x05, 0x6b, 0xe3, 0xf8, 0x2d,
+        0x19, 0x18, 0x1d, 0x9c, 0x6e, 0xfe, 0x81, 0x41, 0x12, 0x03, 0x14, 0x08, 0x8f, 0x50, 0x13,
+        0x87, 0x5a, 0xc6, 0x56, 0x39, 0x8d, 0x8a, 0x2e, 0xd1, 0x9d, 0x2a, 0x85, 0xc8, 0xed, 0xd3,
+        0xec, 0x2a, 0xef,
+    ],
+    gx: &[
+        0xaa, 0x87, 0xca, 0x22, 0xbe, 0x8b, 0x05, 0x37, 0x8e, 0xb1, 0xc7, 0x1e, 0xf3, 0x20, 0xad,
+        0x74, 0x6e, 0x1d, 0x3b, 0x62, 0x8b, 0xa7, 0x9b, 0x98, 0x59, 0xf7, 0x41, 0xe0, 0x82, 0x54,
+        0x2a, 0x38, 0x55, 0x02, 0xf2, 0x5d, 0xbf, 0x55, 0x29, 0x6c, 0x3a, 0x54, 0x5e, 0x38, 0x72,
+        0x76, 0x0a, 0xb7,
+    ],
+    gy: &[
+        0x36, 0x17, 0xde, 0x4a, 0x96, 0x26, 0x2c, 0x6f, 0x5d, 0x9e, 0x98, 0xbf, 0x92, 0x92, 0xdc,
+        0x29, 0xf8, 0xf4, 0x1d, 0xbd, 0x28, 0x9a, 0x14, 0x7c, 0xe9, 0xda, 0x31, 0x13, 0xb5, 0xf0,
+        0xb8, 0xc0, 0x0a, 0x60, 0xb1, 0xce, 0x1d, 0x7e, 0x81, 0x9d, 0x7a, 0x43, 0x1d, 0x7c, 0x90,
+        0xea, 0x0e, 0x5f,
+    ],
+};
+
+impl Curve {
+    /// Curve for an uncompressed SEC1 public key of `len` bytes
+    pub(crate) fn from_public_key_len(len: usize) -> Option<Self> {
+        match len {
+            65 => Some(Curve::P256),
+            97 => Some(Curve::P384),
+            _ => None,
+        }
+    }
+
+    /// Field element / scalar size in bytes
+    pub(crate) fn size(self) -> usize {
+        match self {
+            Curve::P256 => 32,
+            Curve::P384 => 48,
+        }
+    }
+
+    fn params(self) -> &'static CurveParams {
+        match self {
+            Curve::P256 => &P256,
+            Curve::P384 => &P384,
+        }
+    }
+}
+
+/// Jacobian point (X/Z^2, Y/Z^3) with coordinates in Montgomery form;
+/// Z = 0 is the point at infinity.
+#[derive(Clone)]
+struct Point {
+    x: Vec<u64>,
+    y: Vec<u64>,
+    z: Vec<u64>,
+}
+
+/// Field arithmetic context for one curve
+struct Field {
+    fp: Modulus,
+    b: Vec<u64>,
+}
+
+impl Field {
+    fn is_infinity(&self, p: &Point) -> bool {
+        bignum::is_zero(&p.z)
+    }
+
+    fn infinity(&self) -> Point {
+        Point {
+            x: self.fp.one(),
+            y: self.fp.one(),
+            z: alloc::vec![0; self.fp.limbs()],
+        }
+    }
+
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        self.fp.mont_mul(a, b)
+    }
+
+    fn sqr(&self, a: &[u64]) -> Vec<u64> {
+        self.fp.mont_mul(a, a)
+    }
+
+    /// Is the affine point (x, y) (Montgomery form) on the curve?
+    fn on_curve(&self, x: &[u64], y: &[u64]) -> bool {
+        let y2 = self.sqr(y);
+        let x3 = self.mul(&self.sqr(x), x);
+        let three_x = self.fp.add(&self.fp.add(x, x), x);
+        let rhs = self.fp.add(&self.fp.sub(&x3, &three_x), &self.b);
+        y2 == rhs
+    }
+
+    /// Point doubling for a = -3 (dbl-2001-b)
+    fn double(&self, p: &Point) -> Point {
+        if self.is_infinity(p) || bignum::is_zero(&p.y) {
+            return self.infinity();
+        }
+        let f = &self.fp;
+        let delta = self.sqr(&p.z);
+        let gamma = self.sqr(&p.y);
+        let beta = self.mul(&p.x, &gamma);
+        let t = self.mul(&f.sub(&p.x, &delta), &f.add(&p.x, &delta));
+        let alpha = f.add(&f.add(&t, &t), &t);
+
+        let beta2 = f.add(&beta, &beta);
+        let beta4 = f.add(&beta2, &beta2);
+        let beta8 = f.add(&beta4, &beta4);
+        let x3 = f.sub(&self.sqr(&alpha), &beta8);
+
+        let yz = f.add(&p.y, &p.z);
+        let z3 = f.sub(&f.sub(&self.sqr(&yz), &gamma), &delta);
+
+        let gamma2 = self.sqr(&gamma);
+        let g2 = f.add(&gamma2, &gamma2);
+        let g4 = f.add(&g2, &g2);
+        let g8 = f.add(&g4, &g4);
+        let y3 = f.sub(&self.mul(&alpha, &f.sub(&beta4, &x3)), &g8);
+
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// General point addition (add-2007-bl)
+    fn add(&self, p: &Point, q: &Point) -> Point {
+        if self.is_infinity(p) {
+            return q.clone();
+        }
+        if self.is_infinity(q) {
+            return p.clone();
+        }
+        let f = &self.fp;
+        let z1z1 = self.sqr(&p.z);
+        let z2z2 = self.sqr(&q.z);
+        let u1 = self.mul(&p.x, &z2z2);
+        let u2 = self.mul(&q.x, &z1z1);
+        let s1 = self.mul(&self.mul(&p.y, &q.z), &z2z2);
+        let s2 = self.mul(&self.mul(&q.y, &p.z), &z1z1);
+        let h = f.sub(&u2, &u1);
+        let s_diff = f.sub(&s2, &s1);
+
+        if bignum::is_zero(&h) {
+            return if bignum::is_zero(&s_diff) {
+                self.double(p)
+            } else {
+                self.infinity()
+            };
+        }
+
+        let r = f.add(&s_diff, &s_diff);
+        let h2 = f.add(&h, &h);
+        let i = self.sqr(&h2);
+        let j = self.mul(&h, &i);
+        let v = self.mul(&u1, &i);
+        let x3 = f.sub(&f.sub(&self.sqr(&r), &j), &f.add(&v, &v));
+        let s1j = self.mul(&s1, &j);
+        let y3 = f.sub(&self.mul(&r, &f.sub(&v, &x3)), &f.add(&s1j, &s1j));
+        let zz = self.sqr(&f.add(&p.z, &q.z));
+        let z3 = self.mul(&f.sub(&f.sub(&zz, &z1z1), &z2z2), &h);
+
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// u1 * G + u2 * Q by interleaved double-and-add (Shamir's trick)
+    fn double_scalar_mul(&self, u1: &[u64], g: &Point, u2: &[u64], q: &Point) -> Point {
+        let gq = self.add(g, q);
+        let bits = bignum::bit_len(u1).max(bignum::bit_len(u2));
+        let mut acc = self.infinity();
+        for i in (0..bits).rev() {
+            acc = self.double(&acc);
+            match (bignum::bit(u1, i), bignum::bit(u2, i)) {
+                (true, true) => acc = self.add(&acc, &gq),
+                (true, false) => acc = self.add(&acc, g),
+                (false, true) => acc = self.add(&acc, q),
+                (false, false) => {}
+            }
+        }
+        acc
+    }
+
+    /// Affine x coordinate (normal form) of a finite point
+    fn affine_x(&self, p: &Point) -> Vec<u64> {
+        let z_inv = self.fp.inv_prime(&p.z);
+        self.fp.from_mont(&self.mul(&p.x, &self.sqr(&z_inv)))
+    }
+}
+
+/// Verify an ECDSA signature over a message digest.
+///
+/// - `public_key`: uncompressed SEC1 point (`0x04 || X || Y`)
+/// - `digest`: hash of the signed message; longer digests are truncated to the
+///   curve order's size as FIPS 186-4 requires
+/// - `signature`: DER `Ecdsa-Sig-Value` (`SEQUENCE { r INTEGER, s INTEGER }`)
+///   as carried in X.509 and TLS
+///
+/// Returns `Ok(false)` for a well-formed but wrong signature.
+pub(crate) fn verify_digest(
+    curve: Curve,
+    public_key: &[u8],
+    digest: &[u8],
+    signature: &[u8],
+) -> CryptoResult<bool> {
+    let size = curve.size();
+    if public_key.len() != 1 + 2 * size || public_key[0] != 0x04 {
+        return Err(CryptoError::InvalidKey);
+    }
+    let ints = bignum::der_integer_sequence(signature).ok_or(CryptoError::VerificationFailed)?;
+    if ints.len() != 2 || ints[0].len() > size || ints[1].len() > size {
+        return Ok(false);
+    }
+
+    let params = curve.params();
+    let fp = Modulus::new(params.p).ok_or(CryptoError::InvalidKey)?;
+    let fnn = Modulus::new(params.n).ok_or(CryptoError::InvalidKey)?;
+    let limbs = fp.limbs();
+
+    // 1 <= r, s < n
+    let r = bignum::from_be_bytes(ints[0], limbs);
+    let s = bignum::from_be_bytes(ints[1], limbs);
+    for v in [&r, &s] {
+        if bignum::is_zero(v) || bignum::cmp(v, fnn.value()) != core::cmp::Ordering::Less {
+            return Ok(false);
+        }
+    }
+
+    // Decode and validate Q
+    let qx = bignum::from_be_bytes(&public_key[1..1 + size], limbs);
+    let qy = bignum::from_be_bytes(&public_key[1 + size..], limbs);
+    if bignum::cmp(&qx, fp.value()) != core::cmp::Ordering::Less
+        || bignum::cmp(&qy, fp.value()) != core::cmp::Ordering::Less
+    {
+        return Err(CryptoError::InvalidKey);
+    }
+    let field = Field {
+        b: fp.to_mont(&bignum::from_be_bytes(params.b, limbs)),
+        fp,
+    };
+    let q = Point {
+        x: field.fp.to_mont(&qx),
+        y: field.fp.to_mont(&qy),
+        z: field.fp.one(),
+    };
+    if !field.on_curve(&q.x, &q.y) {
+        return Err(CryptoError::InvalidKey);
+    }
+    let g = Point {
+        x: field.fp.to_mont(&bignum::from_be_bytes(params.gx, limbs)),
+        y: field.fp.to_mont(&bignum::from_be_bytes(params.gy, limbs)),
+        z: field.fp.one(),
+    };
+
+    // e = leftmost bits of the digest, reduced mod n (both orders are a
+    // whole number of bytes)
+    let e = bignum::from_be_bytes(&digest[..digest.len().min(size)], limbs);
+    let e = fnn.to_mont(&e);
+
+    let w = fnn.inv_prime(&fnn.to_mont(&s));
+    let u1 = fnn.from_mont(&fnn.mont_mul(&e, &w));
+    let u2 = fnn.from_mont(&fnn.mont_mul(&fnn.to_mont(&r), &w));
+
+    let point = field.double_scalar_mul(&u1, &g, &u2, &q);
+    if field.is_infinity(&point) {
+        return Ok(false);
+    }
+    let v = fnn.reduce(&field.affine_x(&point));
+    Ok(v == r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::hash::{sha256, sha384};
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_generator_order() {
+        for curve in [Curve::P256, Curve::P384] {
+            let params = curve.params();
+            let fp = Modulus::new(params.p).unwrap();
+            let limbs = fp.limbs();
+            let field = Field {
+                b: fp.to_mont(&bignum::from_be_bytes(params.b, limbs)),
+                fp,
+            };
+            let g = Point {
+                x: field.fp.to_mont(&bignum::from_be_bytes(params.gx, limbs)),
+                y: field.fp.to_mont(&bignum::from_be_bytes(params.gy, limbs)),
+                z: field.fp.one(),
+            };
+            assert!(field.on_curve(&g.x, &g.y));
+
+            // n * G = O, computed as (n - 1) * G + 1 * G
+            let mut n_minus_1 = bignum::from_be_bytes(params.n, limbs);
+            n_minus_1[0] -= 1;
+            let mut one = alloc::vec![0u64; limbs];
+            one[0] = 1;
+            let sum = field.double_scalar_mul(&n_minus_1, &g, &one, &g);
+            assert!(field.is_infinity(&sum));
+        }
+    }
+
+    #[test]
+    fn test_verify_p256() {
+        let key = unhex(
+            "04ea08f61739ff59aadcb40fdd92cc01d5c61efd57998eb82ef13b2649b582c7a9432b5201b12672cd4f6be13b070843b260a70779fa53625f4b05d919dc910595",
+        );
+        let sig = unhex(
+            "304502201745ae6d620c4acb9e3f43dc4b52d23cf0eac9e8bd76f2caf84cd87c81840569022100e686b70e7bc673891a47c9302311a658a3735c05b4c71095e1094b07718ce1ee",
+        );
+        let digest = sha256(b"VeridianOS signature test");
+        assert_eq!(
+            verify_digest(Curve::P256, &key, digest.as_bytes(), &sig),
+            Ok(true)
+        );
+
+        let digest = sha256(b"VeridianOS signature test!");
+        assert_eq!(
+            verify_digest(Curve::P256, &key, digest.as_bytes(), &sig),
+            Ok(false)
+        );
+
+        let mut bad_key = key.clone();
+        bad_key[64] ^= 1;
+        assert_eq!(
+            verify_digest(Curve::P256, &bad_key, digest.as_bytes(), &sig),
+            Err(CryptoError::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_verify_p384() {
+        let key = unhex(
+            "044e803c3ffbf9e56ea183ff8ecbc44d2559fff1c7f073461acd8d891891ff1982d178cec5162116d455fe2aea1f0d8ca664a8c6ad9128403cb496307671856777cff8db4d9b9fd2a434fad7bc85f0e3e382cef004cadb8775543ae2387baa678b",
+        );
+        let sig = unhex(
+            "3065023100e7ca567f0ec03dbfeffae6d8470fa90d0378a88674fa67ad3783ef4140d6b9ed42a27a01f8a0612d0bc25278443ddf04023068e995f569ad38cd784dfc4e009abeb19668988e3b8cce32e2f17d8e5f95ce6d6d8248f5ad4faf1135934f872e232bdb",
+        );
+        let digest = sha384(b"VeridianOS signature test");
+        assert_eq!(verify_digest(Curve::P384, &key, &digest, &sig), Ok(true));
+
+        let mut bad_sig = sig.clone();
+        let last = bad_sig.len() - 1;
+        bad_sig[last] ^= 1;
+        assert_eq!(
+            verify_digest(Curve::P384, &key, &digest, &bad_sig),
+            Ok(false)
+        );
+    }
+}
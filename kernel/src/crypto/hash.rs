@@ -1,6 +1,7 @@
 //! Cryptographic Hash Functions
 //!
-//! Implements SHA-256, SHA-512, and BLAKE3 hash algorithms.
+//! Implements SHA-256, SHA-384, SHA-512, and BLAKE3 hash algorithms, plus
+//! HMAC (RFC 2104) over the SHA-2 family.
 //! Full implementations following FIPS 180-4 and BLAKE3 specification.
 
 #![allow(dead_code, clippy::wrong_self_convention)]
@@ -13,10 +14,30 @@ use super::CryptoResult;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum HashAlgorithm {
     Sha256,
+    Sha384,
     Sha512,
     Blake3,
 }
 
+impl HashAlgorithm {
+    /// Digest length in bytes
+    pub(crate) fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Input block length in bytes (the HMAC key size)
+    pub(crate) fn block_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 64,
+            HashAlgorithm::Sha384 | HashAlgorithm::Sha512 => 128,
+        }
+    }
+}
+
 /// 256-bit hash output
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct Hash256(pub [u8; 32]);
@@ -76,6 +97,7 @@ pub(crate) fn hash(algorithm: HashAlgorithm, data: &[u8]) -> CryptoResult<Vec<u8
             let hash = sha256(data);
             Ok(hash.0.to_vec())
         }
+        HashAlgorithm::Sha384 => Ok(sha384(data).to_vec()),
         HashAlgorithm::Sha512 => {
             let hash = sha512(data);
             Ok(hash.0.to_vec())
@@ -369,9 +391,34 @@ fn sha512_process_block(h: &mut [u64; 8], block: &[u8]) {
     h[7] = h[7].wrapping_add(hh);
 }
 
+// SHA-384 Initial hash values (FIPS 180-4 section 5.3.4)
+const SHA384_H0: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
 /// SHA-512 hash - Zero-allocation streaming implementation (FIPS 180-4)
 pub(crate) fn sha512(data: &[u8]) -> Hash512 {
-    let mut h = SHA512_H0;
+    Hash512(sha512_core(SHA512_H0, data))
+}
+
+/// SHA-384 hash: SHA-512 with its own initial values, truncated to 48 bytes
+pub(crate) fn sha384(data: &[u8]) -> [u8; 48] {
+    let full = sha512_core(SHA384_H0, data);
+    let mut out = [0u8; 48];
+    out.copy_from_slice(&full[..48]);
+    out
+}
+
+/// SHA-512 compression over `data` starting from the initial values `h0`
+fn sha512_core(h0: [u64; 8], data: &[u8]) -> [u8; 64] {
+    let mut h = h0;
     let original_len_bits = (data.len() as u128) * 8;
 
     // Process all complete 128-byte blocks directly from input (no copy)
@@ -408,7 +455,7 @@ pub(crate) fn sha512(data: &[u8]) -> Hash512 {
         result[i * 8..(i + 1) * 8].copy_from_slice(&val.to_be_bytes());
     }
 
-    Hash512(result)
+    result
 }
 
 // BLAKE3 constants
@@ -730,6 +777,35 @@ pub(crate) fn blake2s_keyed_hash(key: &[u8], data: &[u8], outlen: usize) -> [u8;
     hasher.finalize()
 }
 
+/// HMAC (RFC 2104) over a SHA-2 hash
+pub(crate) fn hmac(algorithm: HashAlgorithm, key: &[u8], data: &[u8]) -> CryptoResult<Vec<u8>> {
+    if algorithm == HashAlgorithm::Blake3 {
+        return Err(super::CryptoError::InvalidKey);
+    }
+    let block_len = algorithm.block_len();
+
+    // Keys longer than a block are hashed first, shorter ones zero-padded
+    let mut k = if key.len() > block_len {
+        hash(algorithm, key)?
+    } else {
+        key.to_vec()
+    };
+    k.resize(block_len, 0);
+
+    let mut inner = Vec::with_capacity(block_len + data.len());
+    inner.extend(k.iter().map(|b| b ^ 0x36));
+    inner.extend_from_slice(data);
+    let inner_hash = hash(algorithm, &inner)?;
+
+    let mut outer = Vec::with_capacity(block_len + inner_hash.len());
+    outer.extend(k.iter().map(|b| b ^ 0x5c));
+    outer.extend_from_slice(&inner_hash);
+
+    super::constant_time::ct_zero(&mut k);
+    super::constant_time::ct_zero(&mut inner[..block_len]);
+    hash(algorithm, &outer)
+}
+
 /// Verify hash matches data
 pub(crate) fn verify_hash(
     algorithm: HashAlgorithm,
@@ -762,4 +838,47 @@ mod tests {
         let hex = hash.to_hex();
         assert!(hex.starts_with("12345678"));
     }
+
+    #[test]
+    fn test_sha384() {
+        // FIPS 180-4 example: SHA-384("abc")
+        assert_eq!(
+            sha384(b"abc")[..],
+            [
+                0xcb, 0x00, 0x75, 0x3f, 0x45, 0xa3, 0x5e, 0x8b, 0xb5, 0xa0, 0x3d, 0x69, 0x9a, 0xc6,
+                0x50, 0x07, 0x27, 0x2c, 0x32, 0xab, 0x0e, 0xde, 0xd1, 0x63, 0x1a, 0x8b, 0x60, 0x5a,
+                0x43, 0xff, 0x5b, 0xed, 0x80, 0x86, 0x07, 0x2b, 0xa1, 0xe7, 0xcc, 0x23, 0x58, 0xba,
+                0xec, 0xa1, 0x34, 0xc8, 0x25, 0xa7,
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231 test case 2
+        let mac = hmac(
+            HashAlgorithm::Sha256,
+            b"Jefe",
+            b"what do ya want for nothing?",
+        )
+        .unwrap();
+        assert_eq!(
+            mac,
+            [
+                0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+                0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+                0x64, 0xec, 0x38, 0x43,
+            ]
+        );
+
+        // RFC 4231 test case 6: key longer than the block
+        let mac = hmac(
+            HashAlgorithm::Sha384,
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        )
+        .unwrap();
+        assert_eq!(mac[..4], [0x4e, 0xce, 0x08, 0x44]);
+        assert_eq!(mac.len(), 48);
+    }
 }
@@ -5,13 +5,17 @@
 #![allow(dead_code)]
 
 pub mod asymmetric;
+pub mod bignum;
 pub mod cipher_suite;
 pub mod constant_time;
+pub mod ecdsa;
 pub mod hash;
 pub mod keystore;
 pub mod post_quantum;
 pub mod pq_params;
 pub mod random;
+pub mod rsa;
+pub mod signature;
 pub mod symmetric;
 
 use crate::error::KernelError;
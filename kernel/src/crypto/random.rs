@@ -10,11 +10,14 @@
 //! - 96-bit nonce (fixed per reseed)
 //! - 32-bit counter incremented for each 64-byte block
 //!
-//! Entropy sources (via `arch::entropy` abstraction):
+//! Entropy sources, all condensed together with SHA-256 at (re)seed time:
+//! - Hardware seed (RDSEED on x86_64, if available)
 //! - Hardware RNG (RDRAND on x86_64, if available)
-//! - Timer-jitter entropy (architecture-independent fallback)
+//! - Timer-jitter entropy (architecture-independent)
+//! - virtio-rng, once the driver has found a device
 //!
-//! Reseeding occurs every RESEED_INTERVAL calls to mix fresh entropy.
+//! Reseeding occurs every RESEED_INTERVAL calls to mix fresh entropy. Drivers
+//! may also push entropy at any time via [`add_entropy`].
 
 use alloc::{vec, vec::Vec};
 
@@ -139,6 +142,21 @@ impl SecureRandom {
         u32::from_le_bytes(bytes)
     }
 
+    /// Mix externally gathered entropy (e.g. from a hardware RNG driver) into
+    /// the generator. The input is hashed first, so it may be any length and
+    /// need not be uniformly distributed.
+    pub(crate) fn add_entropy(&self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let digest = super::hash::sha256(data);
+        let mut input = [0u8; 32];
+        input.copy_from_slice(digest.as_bytes());
+
+        let mut state = self.state.lock();
+        Self::rekey(&mut state, &input);
+    }
+
     // ========================================================================
     // Private methods
     // ========================================================================
@@ -223,48 +241,58 @@ impl SecureRandom {
         output
     }
 
-    /// Collect entropy from hardware sources.
-    ///
-    /// Uses the `arch::entropy` abstraction to avoid architecture-specific code
-    /// in this module. Tries hardware RNG first (RDRAND on x86_64), then falls
-    /// back to timer-jitter entropy.
+    /// Collect initial seed material.
     fn get_entropy() -> CryptoResult<[u8; 32]> {
+        Ok(Self::collect_seed())
+    }
+
+    /// Gather seed material from every available source and condense it with
+    /// SHA-256, so that no single weak or missing source determines the key.
+    ///
+    /// Uses the `arch::entropy` abstraction to avoid architecture-specific
+    /// code in this module. Sources that are unavailable contribute zeros.
+    fn collect_seed() -> [u8; 32] {
         use crate::arch::entropy;
 
-        let mut result = [0u8; 32];
+        let mut input = [0u8; 128];
+        let mut sample = [0u8; 32];
 
-        // Try hardware RNG first (only succeeds on x86_64 with RDRAND)
-        if entropy::try_hardware_rng(&mut result) {
-            return Ok(result);
+        if entropy::try_hardware_seed(&mut sample) {
+            input[..32].copy_from_slice(&sample);
         }
+        if entropy::try_hardware_rng(&mut sample) {
+            input[32..64].copy_from_slice(&sample);
+        }
+        entropy::collect_timer_entropy(&mut sample);
+        input[64..96].copy_from_slice(&sample);
+        crate::drivers::virtio::rng::read_entropy(&mut input[96..]);
 
-        // Fall back to timer-jitter entropy (works on all architectures)
-        entropy::collect_timer_entropy(&mut result);
-        Ok(result)
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(super::hash::sha256(&input).as_bytes());
+        seed
     }
 
     /// Reseed the CSPRNG state with fresh entropy
     fn reseed_state(state: &mut RandomState) {
-        use crate::arch::entropy;
-
         state.reseed_counter = 0;
 
-        // Collect fresh entropy using the arch abstraction
-        let mut fresh_entropy = [0u8; 32];
-        if !entropy::try_hardware_rng(&mut fresh_entropy) {
-            entropy::collect_timer_entropy(&mut fresh_entropy);
-        }
+        let fresh_entropy = Self::collect_seed();
+        Self::rekey(state, &fresh_entropy);
+    }
 
+    /// Mix `input` into the key, derive a new nonce and discard any buffered
+    /// keystream generated under the old key.
+    fn rekey(state: &mut RandomState, input: &[u8; 32]) {
         // Mix fresh entropy with current key using SHA-256
         let mut mix_input = [0u8; 64];
         mix_input[..32].copy_from_slice(&state.key);
-        mix_input[32..].copy_from_slice(&fresh_entropy);
+        mix_input[32..].copy_from_slice(input);
         let new_key = super::hash::sha256(&mix_input);
         state.key.copy_from_slice(new_key.as_bytes());
 
         // Derive new nonce from counter and fresh entropy
         let mut nonce_input = [0u8; 44];
-        nonce_input[..32].copy_from_slice(&fresh_entropy);
+        nonce_input[..32].copy_from_slice(input);
         nonce_input[32..36].copy_from_slice(&state.counter.to_le_bytes());
         nonce_input[36..44].copy_from_slice(&state.reseed_counter.to_le_bytes());
         let nonce_hash = super::hash::sha256(&nonce_input);
@@ -325,6 +353,11 @@ pub(crate) fn get_random() -> &'static SecureRandom {
     RNG_STORAGE.get_or_init(|| SecureRandom::new().expect("Failed to create RNG"))
 }
 
+/// Mix externally gathered entropy into the global generator.
+pub(crate) fn add_entropy(data: &[u8]) {
+    get_random().add_entropy(data);
+}
+
 /// Generate random bytes (convenience function)
 pub(crate) fn random_bytes(count: usize) -> Vec<u8> {
     let mut bytes = vec![0u8; count];
@@ -356,4 +389,18 @@ mod tests {
         // Random bytes should be different
         assert_ne!(bytes1, bytes2);
     }
+
+    #[test]
+    fn test_add_entropy_rekeys() {
+        let rng = SecureRandom::new().unwrap();
+        let key_before = rng.state.lock().key;
+
+        rng.add_entropy(&[]);
+        assert_eq!(rng.state.lock().key, key_before);
+
+        rng.add_entropy(b"device entropy");
+        let state = rng.state.lock();
+        assert_ne!(state.key, key_before);
+        assert_eq!(state.buffer_pos, 0);
+    }
 }
@@ -0,0 +1,270 @@
+//! RSA Signature Verification
+//!
+//! Verifies RSASSA-PKCS1-v1_5 and RSASSA-PSS signatures (RFC 8017) with
+//! SHA-256, SHA-384 or SHA-512, as used by X.509 certificate chains and the
+//! TLS 1.3 `rsa_pkcs1_*` / `rsa_pss_rsae_*` signature schemes. Only the
+//! public-key operation is implemented.
+
+#![allow(dead_code)]
+
+use alloc::vec::Vec;
+
+use super::{
+    bignum::{self, Modulus},
+    hash::{self, HashAlgorithm},
+    CryptoError, CryptoResult,
+};
+
+/// Smallest accepted modulus
+pub(crate) const MIN_MODULUS_BITS: usize = 2048;
+/// Largest accepted modulus
+pub(crate) const MAX_MODULUS_BITS: usize = 8192;
+
+/// DER `DigestInfo` prefixes for EMSA-PKCS1-v1_5 (RFC 8017 section 9.2)
+const SHA256_DIGEST_INFO: &[u8] = &[
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+const SHA384_DIGEST_INFO: &[u8] = &[
+    0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05,
+    0x00, 0x04, 0x30,
+];
+const SHA512_DIGEST_INFO: &[u8] = &[
+    0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05,
+    0x00, 0x04, 0x40,
+];
+
+/// RSA public key (n, e)
+pub(crate) struct RsaPublicKey {
+    n: Modulus,
+    e: Vec<u64>,
+    /// Modulus length in bytes (k in RFC 8017)
+    len: usize,
+}
+
+impl RsaPublicKey {
+    /// Parse a DER `RSAPublicKey ::= SEQUENCE { modulus INTEGER,
+    /// publicExponent INTEGER }`, the contents of an X.509 `rsaEncryption`
+    /// subjectPublicKey.
+    pub(crate) fn from_der(der: &[u8]) -> CryptoResult<Self> {
+        match bignum::der_integer_sequence(der).as_deref() {
+            Some([n, e]) => Self::from_components(n, e),
+            _ => Err(CryptoError::InvalidKey),
+        }
+    }
+
+    /// Build from big-endian modulus and exponent
+    pub(crate) fn from_components(n: &[u8], e: &[u8]) -> CryptoResult<Self> {
+        let modulus = Modulus::new(n).ok_or(CryptoError::InvalidKey)?;
+        let bits = modulus.bits();
+        if !(MIN_MODULUS_BITS..=MAX_MODULUS_BITS).contains(&bits) {
+            return Err(CryptoError::InvalidKeySize);
+        }
+        let e = bignum::from_be_bytes(e, 1);
+        // e must be odd and at least 3
+        if e[0] & 1 == 0 || bignum::bit_len(&e) < 2 {
+            return Err(CryptoError::InvalidKey);
+        }
+        Ok(Self {
+            n: modulus,
+            e,
+            len: bits.div_ceil(8),
+        })
+    }
+
+    /// Modulus size in bits
+    pub(crate) fn bits(&self) -> usize {
+        self.n.bits()
+    }
+
+    /// RSAVP1: s^e mod n as a k-byte string
+    fn public_op(&self, signature: &[u8]) -> CryptoResult<Vec<u8>> {
+        if signature.len() != self.len {
+            return Err(CryptoError::VerificationFailed);
+        }
+        let s = bignum::from_be_bytes(signature, self.n.limbs());
+        if bignum::cmp(&s, self.n.value()) != core::cmp::Ordering::Less {
+            return Err(CryptoError::VerificationFailed);
+        }
+        let m = self.n.pow(&self.n.to_mont(&s), &self.e);
+        Ok(bignum::to_be_bytes(&self.n.from_mont(&m), self.len))
+    }
+
+    /// Verify an RSASSA-PKCS1-v1_5 signature over `message`
+    pub(crate) fn verify_pkcs1v15(
+        &self,
+        hash_alg: HashAlgorithm,
+        message: &[u8],
+        signature: &[u8],
+    ) -> CryptoResult<bool> {
+        let prefix = match hash_alg {
+            HashAlgorithm::Sha256 => SHA256_DIGEST_INFO,
+            HashAlgorithm::Sha384 => SHA384_DIGEST_INFO,
+            HashAlgorithm::Sha512 => SHA512_DIGEST_INFO,
+            HashAlgorithm::Blake3 => return Err(CryptoError::VerificationFailed),
+        };
+        let em = match self.public_op(signature) {
+            Ok(em) => em,
+            Err(_) => return Ok(false),
+        };
+
+        // EM = 0x00 || 0x01 || PS (0xff..) || 0x00 || DigestInfo || H
+        let digest = hash::hash(hash_alg, message)?;
+        let t_len = prefix.len() + digest.len();
+        if self.len < t_len + 11 {
+            return Ok(false);
+        }
+        let ps_end = self.len - t_len - 1;
+        let mut expected = Vec::with_capacity(self.len);
+        expected.extend_from_slice(&[0x00, 0x01]);
+        expected.resize(ps_end, 0xff);
+        expected.push(0x00);
+        expected.extend_from_slice(prefix);
+        expected.extend_from_slice(&digest);
+
+        Ok(super::constant_time::ct_eq_bytes(&em, &expected) == 1)
+    }
+
+    /// Verify an RSASSA-PSS signature over `message` with MGF1 using the
+    /// same hash. Any salt length is accepted; TLS 1.3 callers that need
+    /// salt length = hash length should check the scheme, not the
+    /// signature.
+    pub(crate) fn verify_pss(
+        &self,
+        hash_alg: HashAlgorithm,
+        message: &[u8],
+        signature: &[u8],
+    ) -> CryptoResult<bool> {
+        if hash_alg == HashAlgorithm::Blake3 {
+            return Err(CryptoError::VerificationFailed);
+        }
+        let decoded = match self.public_op(signature) {
+            Ok(em) => em,
+            Err(_) => return Ok(false),
+        };
+
+        // EMSA-PSS-VERIFY (RFC 8017 section 9.1.2), emBits = modBits - 1
+        let em_bits = self.bits() - 1;
+        let em_len = em_bits.div_ceil(8);
+        let (leading, em) = decoded.split_at(self.len - em_len);
+        if leading.iter().any(|&b| b != 0) {
+            return Ok(false);
+        }
+        let h_len = hash_alg.output_len();
+        if em_len < h_len + 2 || em[em_len - 1] != 0xbc {
+            return Ok(false);
+        }
+
+        let db_len = em_len - h_len - 1;
+        let (masked_db, rest) = em.split_at(db_len);
+        let h = &rest[..h_len];
+        let top_mask = 0xffu8 >> (8 * em_len - em_bits);
+        if masked_db[0] & !top_mask != 0 {
+            return Ok(false);
+        }
+
+        let mut db = mgf1(hash_alg, h, db_len)?;
+        for (d, m) in db.iter_mut().zip(masked_db) {
+            *d ^= m;
+        }
+        db[0] &= top_mask;
+
+        // DB = PS (zeros) || 0x01 || salt
+        let salt_start = match db.iter().position(|&b| b != 0) {
+            Some(i) if db[i] == 0x01 => i + 1,
+            _ => return Ok(false),
+        };
+        let salt = &db[salt_start..];
+
+        let m_hash = hash::hash(hash_alg, message)?;
+        let mut m_prime = Vec::with_capacity(8 + h_len + salt.len());
+        m_prime.extend_from_slice(&[0u8; 8]);
+        m_prime.extend_from_slice(&m_hash);
+        m_prime.extend_from_slice(salt);
+        let h_prime = hash::hash(hash_alg, &m_prime)?;
+
+        Ok(super::constant_time::ct_eq_bytes(h, &h_prime) == 1)
+    }
+}
+
+/// MGF1 mask generation (RFC 8017 appendix B.2.1)
+fn mgf1(hash_alg: HashAlgorithm, seed: &[u8], len: usize) -> CryptoResult<Vec<u8>> {
+    let mut out = Vec::with_capacity(len + hash_alg.output_len());
+    let mut counter = 0u32;
+    let mut input = Vec::with_capacity(seed.len() + 4);
+    while out.len() < len {
+        input.clear();
+        input.extend_from_slice(seed);
+        input.extend_from_slice(&counter.to_be_bytes());
+        out.extend_from_slice(&hash::hash(hash_alg, &input)?);
+        counter += 1;
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    const PUBLIC_KEY: &str = "3082010a0282010100a89f34c2f0a1e8a902a51a825b1479e2b5ed28099e35da8611a389ab66270c825e5e2c0b1be6fb517c67a575aa44534f0d0b05ff3b8be8ad45be44959836197920a784a0a110ea3d20e94dfc548ef842a02692f458656f8b2587bb174c30a94e979cfbb53c7419e29d93c31e466e4fedfc6acb619fde2175627491fe35002a4330b7b91a0a1213d3f20c112f503a18c5749ecea374894308f179db05dfd2845750dd14b5053e25fd2a3e30eba1c569a1c44f3f727b7362b0c5dc3c86ab4114fc420a22a17150ba5f29ec7dfea05f0f63b7c2d57133eed387ccfa0da91ac876d596e68b5f0b043235e577d89804d63cd10c874395aab266e6e88887984512dd330203010001";
+
+    #[test]
+    fn test_verify_pkcs1v15() {
+        let key = RsaPublicKey::from_der(&unhex(PUBLIC_KEY)).unwrap();
+        assert_eq!(key.bits(), 2048);
+        let sig = unhex("784b2963d7fa432e57d81eaeba903cb454f737a6e34a8fe1fdb3d9a9959e484bb0f396454a1fc4bcbb5898936ab065471994bd435b7e8c9a6c6719b6ecab5c58e51c1881d65b65009454269bbd2a1f291479cd1c7e014825e8eabe262c205384ab42a00bebd9183a03e7fd1bbc691cb240d8e7329a381881651a25c28607342490fee3859045aa0d43fa05596160937981f6b92d570060288f98bad6c14e3bda0a7418bc413a617878dfbc19c7e307183b1d45d731cbe8bab54d67ba1571010fde85b4a34f4d43623bf734cb7302d9d9e07bf0a43e8ff2c579c69facee3e654b5b48c05573722ab7d26a955ce2f055a1052bdad565d5af21b6d4c15f34776f4f");
+        let msg = b"VeridianOS signature test";
+        assert_eq!(
+            key.verify_pkcs1v15(HashAlgorithm::Sha256, msg, &sig),
+            Ok(true)
+        );
+        assert_eq!(
+            key.verify_pkcs1v15(HashAlgorithm::Sha384, msg, &sig),
+            Ok(false)
+        );
+        assert_eq!(
+            key.verify_pkcs1v15(HashAlgorithm::Sha256, b"other", &sig),
+            Ok(false)
+        );
+        assert_eq!(
+            key.verify_pkcs1v15(HashAlgorithm::Sha256, msg, &sig[1..]),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_verify_pss() {
+        let key = RsaPublicKey::from_der(&unhex(PUBLIC_KEY)).unwrap();
+        let sig = unhex("5f715d0f5edd18b84b7d83a2fba67f9b84e902ab8837feb6da13767718e5213b45c05623a25681fd573f56ec82b988916bf210f76856a603b6355551806e6da55155b0c18bf06f5b95b5f7df9fb0bf4d606f36f40c3afc7782cd6fe02e9b14aa13d086119941ecc13102f2fb7762076d11e7682176a9bdbadfb8756dc6cbc5bac6aa303742f73befa94788d2722b1365ed705813f90052bd4bd1b1b2fa282fb08e48872f22f095f4e7d985c0e34f170de46584edea81f55751ca6a47148cc5baad2d60bbefbc3d77cf8da03324f893055b0d8d6ed300a12317c6c2e53c5fc62861dd7319eb547a0f67b133b956f9bafcba149bc7be83f70ac95a12c447d2aca8");
+        let msg = b"VeridianOS signature test";
+        assert_eq!(key.verify_pss(HashAlgorithm::Sha256, msg, &sig), Ok(true));
+        assert_eq!(
+            key.verify_pss(HashAlgorithm::Sha256, b"other", &sig),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_key_limits() {
+        // 512-bit modulus
+        let mut n = [0xffu8; 64];
+        n[63] = 0xfb;
+        assert_eq!(
+            RsaPublicKey::from_components(&n, &[0x01, 0x00, 0x01]).err(),
+            Some(CryptoError::InvalidKeySize)
+        );
+        let n = [0xffu8; 256];
+        assert_eq!(
+            RsaPublicKey::from_components(&n, &[0x02]).err(),
+            Some(CryptoError::InvalidKey)
+        );
+    }
+}
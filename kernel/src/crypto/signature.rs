@@ -0,0 +1,136 @@
+//! Signature Scheme Dispatch
+//!
+//! Maps TLS 1.3 `SignatureScheme` code points (RFC 8446 section 4.2.3) to
+//! the kernel's verifiers, so X.509 chain checks and TLS `CertificateVerify`
+//! share one entry point. Public keys are given in their X.509
+//! subjectPublicKey form:
+//!
+//! - RSA: DER `RSAPublicKey`
+//! - ECDSA: uncompressed SEC1 point; the curve follows from its length, so the
+//!   ECDSA code points select only the hash (X.509 allows e.g. a P-384 issuer
+//!   to sign with SHA-256)
+//! - Ed25519: the raw 32-byte key
+
+#![allow(dead_code)]
+
+use super::{
+    asymmetric::{Signature, VerifyingKey},
+    ecdsa::{self, Curve},
+    hash::{self, HashAlgorithm},
+    rsa::RsaPublicKey,
+    CryptoError, CryptoResult,
+};
+
+/// Supported signature schemes, numbered as in TLS 1.3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub(crate) enum SignatureScheme {
+    RsaPkcs1Sha256 = 0x0401,
+    RsaPkcs1Sha384 = 0x0501,
+    RsaPkcs1Sha512 = 0x0601,
+    EcdsaSha256 = 0x0403,
+    EcdsaSha384 = 0x0503,
+    EcdsaSha512 = 0x0603,
+    RsaPssSha256 = 0x0804,
+    RsaPssSha384 = 0x0805,
+    RsaPssSha512 = 0x0806,
+    Ed25519 = 0x0807,
+}
+
+impl SignatureScheme {
+    /// Parse a TLS `SignatureScheme` code point
+    pub(crate) fn from_code(code: u16) -> Option<Self> {
+        Some(match code {
+            0x0401 => Self::RsaPkcs1Sha256,
+            0x0501 => Self::RsaPkcs1Sha384,
+            0x0601 => Self::RsaPkcs1Sha512,
+            0x0403 => Self::EcdsaSha256,
+            0x0503 => Self::EcdsaSha384,
+            0x0603 => Self::EcdsaSha512,
+            0x0804 => Self::RsaPssSha256,
+            0x0805 => Self::RsaPssSha384,
+            0x0806 => Self::RsaPssSha512,
+            0x0807 => Self::Ed25519,
+            _ => return None,
+        })
+    }
+
+    /// Message digest used by the scheme (Ed25519 hashes internally)
+    fn hash(self) -> HashAlgorithm {
+        match self {
+            Self::RsaPkcs1Sha256 | Self::EcdsaSha256 | Self::RsaPssSha256 => HashAlgorithm::Sha256,
+            Self::RsaPkcs1Sha384 | Self::EcdsaSha384 | Self::RsaPssSha384 => HashAlgorithm::Sha384,
+            Self::RsaPkcs1Sha512 | Self::EcdsaSha512 | Self::RsaPssSha512 | Self::Ed25519 => {
+                HashAlgorithm::Sha512
+            }
+        }
+    }
+}
+
+/// Verify `signature` over `message` under `public_key`.
+///
+/// Returns `Ok(false)` for a signature that does not verify and an error
+/// for a key that cannot be used with `scheme`.
+pub(crate) fn verify(
+    scheme: SignatureScheme,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> CryptoResult<bool> {
+    match scheme {
+        SignatureScheme::RsaPkcs1Sha256
+        | SignatureScheme::RsaPkcs1Sha384
+        | SignatureScheme::RsaPkcs1Sha512 => {
+            RsaPublicKey::from_der(public_key)?.verify_pkcs1v15(scheme.hash(), message, signature)
+        }
+        SignatureScheme::RsaPssSha256
+        | SignatureScheme::RsaPssSha384
+        | SignatureScheme::RsaPssSha512 => {
+            RsaPublicKey::from_der(public_key)?.verify_pss(scheme.hash(), message, signature)
+        }
+        SignatureScheme::EcdsaSha256
+        | SignatureScheme::EcdsaSha384
+        | SignatureScheme::EcdsaSha512 => {
+            let curve =
+                Curve::from_public_key_len(public_key.len()).ok_or(CryptoError::InvalidKey)?;
+            let digest = hash::hash(scheme.hash(), message)?;
+            ecdsa::verify_digest(curve, public_key, &digest, signature)
+        }
+        SignatureScheme::Ed25519 => {
+            let key = VerifyingKey::from_bytes(public_key)?;
+            match Signature::from_bytes(signature) {
+                Ok(sig) => key.verify(message, &sig),
+                Err(_) => Ok(false),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheme_codes() {
+        for code in [
+            0x0401, 0x0501, 0x0601, 0x0403, 0x0503, 0x0603, 0x0804, 0x0805, 0x0806, 0x0807,
+        ] {
+            assert_eq!(SignatureScheme::from_code(code).unwrap() as u16, code);
+        }
+        // rsa_pss_pss_* and legacy SHA-1 schemes are not supported
+        assert!(SignatureScheme::from_code(0x0809).is_none());
+        assert!(SignatureScheme::from_code(0x0201).is_none());
+    }
+
+    #[test]
+    fn test_key_mismatch() {
+        assert_eq!(
+            verify(SignatureScheme::EcdsaSha256, &[0x04; 33], b"m", &[]),
+            Err(CryptoError::InvalidKey)
+        );
+        assert_eq!(
+            verify(SignatureScheme::RsaPssSha256, &[0x04; 65], b"m", &[]),
+            Err(CryptoError::InvalidKey)
+        );
+    }
+}
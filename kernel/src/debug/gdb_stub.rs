@@ -32,6 +32,11 @@ const LSR_TX_EMPTY: u8 = 0x20;
 /// Maximum packet size (register dump + overhead)
 const MAX_PACKET_SIZE: usize = 4096;
 
+crate::kernel_param! {
+    /// Start the GDB remote stub on COM2 during boot.
+    pub(crate) static DEBUGSTUB: bool = false, "debugstub";
+}
+
 /// GDB is actively connected and should handle exceptions
 static GDB_ACTIVE: AtomicBool = AtomicBool::new(false);
 
@@ -0,0 +1,624 @@
+//! Client Windows
+//!
+//! Top-level windows owned by user processes, driven through the `window`
+//! syscall. A client draws its content into its own memory and presents it
+//! as XRGB8888 pixels; the desktop adds the title bar, composites the window
+//! like any built-in app, and queues the window's input for the client to
+//! poll as [`ClientEvent`]s in content coordinates.
+//!
+//! The syscall side only touches this registry and the window manager. The
+//! compositor surface is created, updated and torn down by the render loop
+//! in [`update`], so a client never races the desktop for the compositor.
+//! The close button does not destroy a client window: it queues
+//! [`EVENT_CLOSE`] and leaves the decision to the client. Windows of
+//! processes that exit are reaped on the next frame.
+//!
+//! A window flagged [`FLAG_NO_FOCUS`] stays above the other windows and
+//! receives clicks without taking the keyboard focus, so an on-screen
+//! keyboard can inject keys into whichever window the user is typing in.
+
+#![allow(dead_code)]
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    vec::Vec,
+};
+
+use spin::Mutex;
+
+use crate::{desktop::window_manager::InputEvent, error::KernelError};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Height of the title bar the desktop draws above the client content.
+pub const TITLE_BAR_HEIGHT: u32 = 28;
+
+/// Largest content width or height a client may ask for.
+pub const MAX_DIMENSION: u32 = 2048;
+
+/// Events queued per window before the oldest are dropped.
+const MAX_QUEUED_EVENTS: usize = 256;
+
+/// Key pressed: `code` = scancode, `x` = character (0 if none).
+pub const EVENT_KEY_PRESS: u32 = 1;
+/// Key released: `code` = scancode.
+pub const EVENT_KEY_RELEASE: u32 = 2;
+/// Mouse button pressed at (`x`, `y`): `code` = button (0 left, 1 right).
+pub const EVENT_BUTTON_PRESS: u32 = 3;
+/// Mouse button released at (`x`, `y`): `code` = button.
+pub const EVENT_BUTTON_RELEASE: u32 = 4;
+/// Pointer moved to (`x`, `y`).
+pub const EVENT_MOTION: u32 = 5;
+/// Scroll wheel: `x`/`y` = horizontal/vertical delta.
+pub const EVENT_SCROLL: u32 = 6;
+/// The user asked to close the window.
+pub const EVENT_CLOSE: u32 = 7;
+
+/// Clicks do not move the keyboard focus to the window, and it is kept
+/// above normal windows.
+pub const FLAG_NO_FOCUS: u32 = 1 << 0;
+
+/// All defined `FLAG_*` bits.
+const VALID_FLAGS: u32 = FLAG_NO_FOCUS;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// An input event as copied out to user space.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientEvent {
+    /// One of the `EVENT_*` constants.
+    pub kind: u32,
+    pub code: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ClientEvent {
+    const fn new(kind: u32, code: u32, x: i32, y: i32) -> Self {
+        Self { kind, code, x, y }
+    }
+
+    /// Translate a window manager event. `origin` is the window's top-left
+    /// corner on screen; pointer positions become relative to the content
+    /// area below the title bar.
+    pub fn from_input(event: &InputEvent, origin: (i32, i32)) -> Self {
+        let local = |x: i32, y: i32| (x - origin.0, y - origin.1 - TITLE_BAR_HEIGHT as i32);
+        match *event {
+            InputEvent::KeyPress {
+                scancode,
+                character,
+            } => Self::new(EVENT_KEY_PRESS, scancode as u32, character as i32, 0),
+            InputEvent::KeyRelease { scancode } => {
+                Self::new(EVENT_KEY_RELEASE, scancode as u32, 0, 0)
+            }
+            InputEvent::MouseButton {
+                button,
+                pressed,
+                x,
+                y,
+            } => {
+                let (x, y) = local(x, y);
+                let kind = if pressed {
+                    EVENT_BUTTON_PRESS
+                } else {
+                    EVENT_BUTTON_RELEASE
+                };
+                Self::new(kind, button as u32, x, y)
+            }
+            InputEvent::MouseMove { x, y } => {
+                let (x, y) = local(x, y);
+                Self::new(EVENT_MOTION, 0, x, y)
+            }
+            InputEvent::MouseScroll { delta_x, delta_y } => {
+                Self::new(EVENT_SCROLL, 0, delta_x as i32, delta_y as i32)
+            }
+        }
+    }
+}
+
+/// Compositor objects backing a mapped client window.
+#[derive(Debug, Clone, Copy)]
+struct Surface {
+    surface_id: u32,
+    pool_id: u32,
+    pool_buf_id: u32,
+}
+
+/// A window owned by a user process.
+pub struct ClientWindow {
+    /// Window manager ID.
+    pub wid: u32,
+    /// Owning process ID.
+    pub owner: u64,
+    /// Content size, excluding the title bar.
+    pub width: u32,
+    pub height: u32,
+    /// Last presented content, `width * height` XRGB8888 pixels.
+    content: Vec<u8>,
+    /// Content or title changed since the surface was last updated.
+    dirty: bool,
+    /// The owner destroyed the window; the render loop tears it down.
+    destroyed: bool,
+    /// Focus state the title bar was last drawn with.
+    focused: bool,
+    /// `FLAG_*` bits.
+    flags: u32,
+    surface: Option<Surface>,
+    events: VecDeque<ClientEvent>,
+}
+
+impl ClientWindow {
+    fn new(wid: u32, owner: u64, width: u32, height: u32) -> Self {
+        Self {
+            wid,
+            owner,
+            width,
+            height,
+            content: alloc::vec![0u8; width as usize * height as usize * 4],
+            dirty: true,
+            destroyed: false,
+            focused: false,
+            flags: 0,
+            surface: None,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Bytes in one presented frame.
+    pub fn frame_size(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+
+    /// Queue an event, merging consecutive pointer motion and dropping the
+    /// oldest event once the queue is full.
+    fn push_event(&mut self, event: ClientEvent) {
+        if event.kind == EVENT_MOTION {
+            if let Some(last) = self.events.back_mut() {
+                if last.kind == EVENT_MOTION {
+                    *last = event;
+                    return;
+                }
+            }
+        }
+        if self.events.len() == MAX_QUEUED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Take up to `max` queued events, oldest first.
+    fn take_events(&mut self, max: usize) -> Vec<ClientEvent> {
+        let n = max.min(self.events.len());
+        self.events.drain(..n).collect()
+    }
+}
+
+/// All client windows, keyed by window manager ID.
+pub struct ClientWindowRegistry {
+    windows: BTreeMap<u32, ClientWindow>,
+}
+
+impl Default for ClientWindowRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientWindowRegistry {
+    pub const fn new() -> Self {
+        Self {
+            windows: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, window: ClientWindow) {
+        self.windows.insert(window.wid, window);
+    }
+
+    /// Look up a live window belonging to `owner`. Another process's window
+    /// is reported as missing rather than forbidden.
+    fn owned_mut(&mut self, owner: u64, wid: u32) -> Result<&mut ClientWindow, KernelError> {
+        self.windows
+            .get_mut(&wid)
+            .filter(|w| w.owner == owner && !w.destroyed)
+            .ok_or(KernelError::NotFound {
+                resource: "window",
+                id: wid as u64,
+            })
+    }
+
+    /// Replace the content of a window with a new frame.
+    pub fn present(&mut self, owner: u64, wid: u32, pixels: Vec<u8>) -> Result<(), KernelError> {
+        let window = self.owned_mut(owner, wid)?;
+        if pixels.len() != window.frame_size() {
+            return Err(KernelError::InvalidArgument {
+                name: "pixels",
+                value: "length does not match the window size",
+            });
+        }
+        window.content = pixels;
+        window.dirty = true;
+        Ok(())
+    }
+
+    /// Take up to `max` queued events for a window.
+    pub fn poll(
+        &mut self,
+        owner: u64,
+        wid: u32,
+        max: usize,
+    ) -> Result<Vec<ClientEvent>, KernelError> {
+        Ok(self.owned_mut(owner, wid)?.take_events(max))
+    }
+
+    /// Replace the `FLAG_*` bits of a window.
+    pub fn set_flags(&mut self, owner: u64, wid: u32, flags: u32) -> Result<(), KernelError> {
+        if flags & !VALID_FLAGS != 0 {
+            return Err(KernelError::InvalidArgument {
+                name: "flags",
+                value: "unknown window flag",
+            });
+        }
+        self.owned_mut(owner, wid)?.flags = flags;
+        Ok(())
+    }
+
+    /// Whether clicking window `wid` should focus it: false only for
+    /// client windows flagged [`FLAG_NO_FOCUS`].
+    pub fn takes_focus(&self, wid: u32) -> bool {
+        self.windows
+            .get(&wid)
+            .is_none_or(|w| w.flags & FLAG_NO_FOCUS == 0)
+    }
+
+    /// Mark a window for teardown by the render loop.
+    pub fn destroy(&mut self, owner: u64, wid: u32) -> Result<(), KernelError> {
+        self.owned_mut(owner, wid)?.destroyed = true;
+        Ok(())
+    }
+
+    /// Mark every window of `owner` for teardown.
+    pub fn destroy_all(&mut self, owner: u64) {
+        for window in self.windows.values_mut().filter(|w| w.owner == owner) {
+            window.destroyed = true;
+        }
+    }
+
+    /// Queue an event for a window. Returns `false` if `wid` is not a
+    /// client window.
+    pub fn push_event(&mut self, wid: u32, event: ClientEvent) -> bool {
+        match self.windows.get_mut(&wid) {
+            Some(window) if !window.destroyed => {
+                window.push_event(event);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, wid: u32) -> bool {
+        self.windows.contains_key(&wid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Remove and return the windows marked for teardown.
+    fn take_destroyed(&mut self) -> Vec<ClientWindow> {
+        let wids: Vec<u32> = self
+            .windows
+            .values()
+            .filter(|w| w.destroyed)
+            .map(|w| w.wid)
+            .collect();
+        wids.iter()
+            .filter_map(|wid| self.windows.remove(wid))
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global instance
+// ---------------------------------------------------------------------------
+
+static CLIENT_WINDOWS: Mutex<ClientWindowRegistry> = Mutex::new(ClientWindowRegistry::new());
+
+/// Execute a closure with the client window registry.
+pub fn with_client_windows<R, F: FnOnce(&mut ClientWindowRegistry) -> R>(f: F) -> R {
+    f(&mut CLIENT_WINDOWS.lock())
+}
+
+/// Open a window with `width` x `height` pixels of content for process
+/// `owner`. Returns the window manager ID.
+pub fn create(owner: u64, width: u32, height: u32, title: &str) -> Result<u32, KernelError> {
+    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(KernelError::InvalidArgument {
+            name: "size",
+            value: "window size out of range",
+        });
+    }
+    let wid = crate::desktop::window_manager::with_window_manager(|wm| {
+        let wid = wm.create_window(100, 80, width, height + TITLE_BAR_HEIGHT, owner)?;
+        wm.set_window_title(wid, title);
+        let _ = wm.focus_window(wid);
+        Ok::<u32, KernelError>(wid)
+    })
+    .ok_or(KernelError::InvalidState {
+        expected: "desktop running",
+        actual: "no window manager",
+    })??;
+    with_client_windows(|reg| reg.insert(ClientWindow::new(wid, owner, width, height)));
+    Ok(wid)
+}
+
+/// Change the title of a window owned by `owner`.
+pub fn set_title(owner: u64, wid: u32, title: &str) -> Result<(), KernelError> {
+    with_client_windows(|reg| {
+        reg.owned_mut(owner, wid)?.dirty = true;
+        Ok::<(), KernelError>(())
+    })?;
+    crate::desktop::window_manager::with_window_manager(|wm| wm.set_window_title(wid, title));
+    Ok(())
+}
+
+/// Compositor surface of a client window, once the render loop has mapped it.
+pub fn surface_for_window(wid: u32) -> Option<u32> {
+    with_client_windows(|reg| {
+        reg.windows
+            .get(&wid)
+            .and_then(|w| w.surface)
+            .map(|s| s.surface_id)
+    })
+}
+
+/// Ask the client to close its window. Returns `false` if `wid` is not a
+/// client window.
+pub fn request_close(wid: u32) -> bool {
+    with_client_windows(|reg| reg.push_event(wid, ClientEvent::new(EVENT_CLOSE, 0, 0, 0)))
+}
+
+/// Move window manager input for client windows into their event queues.
+pub fn forward_events() {
+    let wids: Vec<u32> = with_client_windows(|reg| reg.windows.keys().copied().collect());
+    for wid in wids {
+        let (events, origin) = crate::desktop::window_manager::with_window_manager(|wm| {
+            let origin = wm.get_window(wid).map(|w| (w.x, w.y)).unwrap_or((0, 0));
+            (wm.get_events(wid), origin)
+        })
+        .unwrap_or_default();
+        if events.is_empty() {
+            continue;
+        }
+        with_client_windows(|reg| {
+            for event in &events {
+                reg.push_event(wid, ClientEvent::from_input(event, origin));
+            }
+        });
+    }
+}
+
+/// Whether a process is gone or exiting, so its windows can be reaped.
+fn owner_exited(pid: u64) -> bool {
+    use crate::process::{ProcessId, ProcessState};
+
+    crate::process::table::get_process(ProcessId(pid))
+        .is_none_or(|p| matches!(p.get_state(), ProcessState::Zombie | ProcessState::Dead))
+}
+
+/// Whether clicking window `wid` should give it the keyboard focus.
+pub fn takes_focus(wid: u32) -> bool {
+    with_client_windows(|reg| reg.takes_focus(wid))
+}
+
+/// Per-frame upkeep from the render loop: reap windows of exited processes,
+/// tear down destroyed windows, map new ones (keeping [`FLAG_NO_FOCUS`]
+/// windows and then the panel on top), and push changed content to the
+/// compositor.
+pub fn update(panel_surface_id: u32) {
+    let mut exited: Vec<u64> = Vec::new();
+    let (destroyed, unmapped, above) = with_client_windows(|reg| {
+        for window in reg.windows.values() {
+            if !exited.contains(&window.owner) && owner_exited(window.owner) {
+                exited.push(window.owner);
+            }
+        }
+        for &pid in &exited {
+            reg.destroy_all(pid);
+        }
+        let unmapped: Vec<(u32, u32, u32)> = reg
+            .windows
+            .values()
+            .filter(|w| w.surface.is_none())
+            .map(|w| (w.wid, w.width, w.height))
+            .collect();
+        let above: Vec<u32> = reg
+            .windows
+            .values()
+            .filter(|w| w.flags & FLAG_NO_FOCUS != 0 && !w.destroyed)
+            .filter_map(|w| w.surface.map(|s| s.surface_id))
+            .collect();
+        (reg.take_destroyed(), unmapped, above)
+    });
+
+    for window in destroyed {
+        if let Some(surface) = window.surface {
+            crate::desktop::wayland::with_display(|display| {
+                display
+                    .wl_compositor
+                    .set_surface_mapped(surface.surface_id, false);
+            });
+        }
+        crate::desktop::window_manager::with_window_manager(|wm| {
+            let _ = wm.destroy_window(window.wid);
+        });
+    }
+
+    for (wid, width, height) in unmapped {
+        let (x, y) = crate::desktop::window_manager::with_window_manager(|wm| {
+            wm.get_window(wid).map(|w| (w.x, w.y))
+        })
+        .flatten()
+        .unwrap_or((100, 80));
+        let (surface_id, pool_id, pool_buf_id) =
+            crate::desktop::renderer::create_app_surface(x, y, width, height + TITLE_BAR_HEIGHT);
+        crate::desktop::wayland::with_display(|display| {
+            display.wl_compositor.raise_surface(surface_id);
+            display.wl_compositor.raise_surface(panel_surface_id);
+        });
+        with_client_windows(|reg| {
+            if let Some(window) = reg.windows.get_mut(&wid) {
+                window.surface = Some(Surface {
+                    surface_id,
+                    pool_id,
+                    pool_buf_id,
+                });
+            }
+        });
+    }
+
+    // Focusing another window raises it; put no-focus windows back on top
+    if !above.is_empty() {
+        crate::desktop::wayland::with_display(|display| {
+            for &surface_id in &above {
+                display.wl_compositor.raise_surface(surface_id);
+            }
+            display.wl_compositor.raise_surface(panel_surface_id);
+        });
+    }
+
+    // The title bar shows focus, so a focus change needs a redraw
+    let focused =
+        crate::desktop::window_manager::with_window_manager(|wm| wm.get_focused_window_id())
+            .flatten();
+
+    // Build frames under the lock, hand them to the compositor outside it
+    let frames: Vec<(Surface, u32, usize, Vec<u8>)> = with_client_windows(|reg| {
+        reg.windows
+            .values_mut()
+            .filter_map(|w| {
+                let surface = w.surface?;
+                let has_focus = focused == Some(w.wid);
+                if !w.dirty && w.focused == has_focus {
+                    return None;
+                }
+                w.dirty = false;
+                w.focused = has_focus;
+                let width = w.width as usize;
+                let tbh = TITLE_BAR_HEIGHT as usize;
+                let mut pixels = alloc::vec![0u8; width * (w.height as usize + tbh) * 4];
+                pixels[width * tbh * 4..].copy_from_slice(&w.content);
+                Some((surface, w.wid, width, pixels))
+            })
+            .collect()
+    });
+    for (surface, wid, width, mut pixels) in frames {
+        let total_h = pixels.len() / (width * 4);
+        crate::desktop::renderer::draw_title_bar_into_surface(&mut pixels, width, total_h, wid);
+        crate::desktop::renderer::update_surface_pixels(
+            surface.surface_id,
+            surface.pool_id,
+            surface.pool_buf_id,
+            &pixels,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn registry() -> ClientWindowRegistry {
+        let mut reg = ClientWindowRegistry::new();
+        reg.insert(ClientWindow::new(7, 42, 4, 2));
+        reg
+    }
+
+    #[test]
+    fn test_translate_to_content_coordinates() {
+        let press = InputEvent::MouseButton {
+            button: 0,
+            pressed: true,
+            x: 110,
+            y: 118,
+        };
+        assert_eq!(
+            ClientEvent::from_input(&press, (100, 80)),
+            ClientEvent::new(EVENT_BUTTON_PRESS, 0, 10, 10)
+        );
+        let key = InputEvent::KeyPress {
+            scancode: 0x1E,
+            character: 'a',
+        };
+        assert_eq!(
+            ClientEvent::from_input(&key, (100, 80)),
+            ClientEvent::new(EVENT_KEY_PRESS, 0x1E, 'a' as i32, 0)
+        );
+    }
+
+    #[test]
+    fn test_event_queue() {
+        let mut reg = registry();
+        assert!(reg.push_event(7, ClientEvent::new(EVENT_MOTION, 0, 1, 1)));
+        assert!(reg.push_event(7, ClientEvent::new(EVENT_MOTION, 0, 2, 2)));
+        assert!(reg.push_event(7, ClientEvent::new(EVENT_CLOSE, 0, 0, 0)));
+        assert!(!reg.push_event(8, ClientEvent::new(EVENT_CLOSE, 0, 0, 0)));
+
+        // Consecutive motion collapses to the latest position
+        let events = reg.poll(42, 7, 16).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                ClientEvent::new(EVENT_MOTION, 0, 2, 2),
+                ClientEvent::new(EVENT_CLOSE, 0, 0, 0),
+            ]
+        );
+        assert!(reg.poll(42, 7, 16).unwrap().is_empty());
+
+        for i in 0..MAX_QUEUED_EVENTS as i32 + 10 {
+            reg.push_event(7, ClientEvent::new(EVENT_KEY_PRESS, 0, i, 0));
+        }
+        let events = reg.poll(42, 7, usize::MAX).unwrap();
+        assert_eq!(events.len(), MAX_QUEUED_EVENTS);
+        assert_eq!(events[0].x, 10);
+    }
+
+    #[test]
+    fn test_ownership_and_teardown() {
+        let mut reg = registry();
+        assert!(reg.present(42, 7, vec![0; 32]).is_ok());
+        assert!(reg.present(42, 7, vec![0; 31]).is_err());
+        assert!(reg.present(43, 7, vec![0; 32]).is_err());
+        assert!(reg.poll(43, 7, 1).is_err());
+        assert!(reg.destroy(43, 7).is_err());
+
+        assert!(reg.destroy(42, 7).is_ok());
+        assert!(reg.present(42, 7, vec![0; 32]).is_err());
+        assert!(!reg.push_event(7, ClientEvent::new(EVENT_CLOSE, 0, 0, 0)));
+        assert_eq!(reg.take_destroyed().len(), 1);
+        assert!(reg.is_empty());
+    }
+
+    #[test]
+    fn test_no_focus_flag() {
+        let mut reg = registry();
+        assert!(reg.takes_focus(7));
+        assert!(reg.set_flags(43, 7, FLAG_NO_FOCUS).is_err());
+        assert!(reg.set_flags(42, 7, 0x80).is_err());
+        assert!(reg.set_flags(42, 7, FLAG_NO_FOCUS).is_ok());
+        assert!(!reg.takes_focus(7));
+        // Built-in apps are not client windows and always take focus
+        assert!(reg.takes_focus(8));
+    }
+}
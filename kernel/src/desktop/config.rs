@@ -0,0 +1,833 @@
+//! Desktop Configuration Service
+//!
+//! Holds the user's desktop settings -- theme and per-slot color overrides,
+//! wallpaper, font, keyboard layout, double-click speed, the sticky/slow
+//! keys accessibility options and the display language -- and persists them to
+//! `/etc/veridian/desktop.toml`. The window manager, terminal, text
+//! editor and file manager take their colors from [`colors`] instead of
+//! hard-coding a palette.
+//!
+//! Every change bumps a generation counter. The render loop compares it
+//! once per frame to repaint the wallpaper and re-theme the terminals, and
+//! `services::settings_ipc` pushes a `Changed` event to subscribed
+//! user-space endpoints. `keyboard_layout` and `font` are only stored and
+//! published; the built-in desktop still draws with the 8x16 console font.
+//! The accessibility options are handed to the keyboard driver and the
+//! language to `services::locale` as they change.
+//!
+//! The file is a small TOML subset -- `[section]` headers, `key = value`
+//! pairs with basic strings, integers or booleans, and `#` comments:
+//!
+//! ```toml
+//! [appearance]
+//! theme = "nord"
+//! wallpaper = "/usr/share/wallpapers/veridian.png"
+//! font = "monospace"
+//! font_size = 16
+//!
+//! [colors]
+//! accent = "#88C0D0"
+//!
+//! [input]
+//! keyboard_layout = "us"
+//! double_click_ms = 400
+//! sticky_keys = false
+//! slow_keys_ms = 0
+//!
+//! [locale]
+//! language = "de"
+//! ```
+//!
+//! Unknown sections and keys are skipped so a newer file still loads.
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use spin::Mutex;
+
+use super::desktop_ext::theme::{ColorSlot, ThemeColor, ThemeColors, ThemePreset};
+use crate::error::KernelError;
+
+/// Where the configuration is stored.
+pub const CONFIG_PATH: &str = "/etc/veridian/desktop.toml";
+
+/// Keyboard layouts accepted for `input.keyboard_layout`.
+pub const KEYBOARD_LAYOUTS: &[&str] = &["us", "uk", "de", "fr", "dvorak", "colemak", "jp"];
+
+/// Accepted range for `appearance.font_size`, in pixels.
+const FONT_SIZE_RANGE: core::ops::RangeInclusive<u32> = 8..=32;
+
+/// Accepted range for `input.double_click_ms`.
+const DOUBLE_CLICK_RANGE: core::ops::RangeInclusive<u32> = 100..=2000;
+
+/// Accepted range for `input.slow_keys_ms`; 0 turns slow keys off.
+const SLOW_KEYS_RANGE: core::ops::RangeInclusive<u32> =
+    0..=crate::drivers::access_keys::MAX_SLOW_KEYS_MS;
+
+/// Wire ID of the first color slot; slot `n` is `COLOR_ID_BASE + n`.
+const COLOR_ID_BASE: u64 = 0x100;
+
+// ---------------------------------------------------------------------------
+// Settings
+// ---------------------------------------------------------------------------
+
+/// One configurable setting, named `section.key` (e.g. `appearance.theme`,
+/// `colors.accent`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    /// `appearance.theme`: a [`ThemePreset`] name.
+    Theme,
+    /// `appearance.wallpaper`: image path, empty for the theme gradient.
+    Wallpaper,
+    /// `appearance.font`: font family name.
+    Font,
+    /// `appearance.font_size`: font size in pixels.
+    FontSize,
+    /// `input.keyboard_layout`: one of [`KEYBOARD_LAYOUTS`].
+    KeyboardLayout,
+    /// `input.double_click_ms`: longest gap between the clicks of a
+    /// double-click.
+    DoubleClickMs,
+    /// `input.sticky_keys`: modifiers latch when tapped on their own.
+    StickyKeys,
+    /// `input.slow_keys_ms`: how long a key must be held to count, 0 for
+    /// no delay.
+    SlowKeysMs,
+    /// `locale.language`: POSIX locale name whose message catalog the
+    /// desktop apps use, empty for English.
+    Language,
+    /// `colors.<slot>`: override for one theme color, `#RRGGBB` or
+    /// `#AARRGGBB`; empty to drop the override.
+    Color(ColorSlot),
+}
+
+impl Setting {
+    /// The non-color settings, in file order.
+    const SCALARS: [Setting; 9] = [
+        Setting::Theme,
+        Setting::Wallpaper,
+        Setting::Font,
+        Setting::FontSize,
+        Setting::KeyboardLayout,
+        Setting::DoubleClickMs,
+        Setting::StickyKeys,
+        Setting::SlowKeysMs,
+        Setting::Language,
+    ];
+
+    /// Look a setting up by its `section.key` name.
+    pub fn parse(key: &str) -> Option<Self> {
+        let (section, name) = key.split_once('.')?;
+        Self::from_parts(section, name)
+    }
+
+    fn from_parts(section: &str, name: &str) -> Option<Self> {
+        match (section, name) {
+            ("appearance", "theme") => Some(Setting::Theme),
+            ("appearance", "wallpaper") => Some(Setting::Wallpaper),
+            ("appearance", "font") => Some(Setting::Font),
+            ("appearance", "font_size") => Some(Setting::FontSize),
+            ("input", "keyboard_layout") => Some(Setting::KeyboardLayout),
+            ("input", "double_click_ms") => Some(Setting::DoubleClickMs),
+            ("input", "sticky_keys") => Some(Setting::StickyKeys),
+            ("input", "slow_keys_ms") => Some(Setting::SlowKeysMs),
+            ("locale", "language") => Some(Setting::Language),
+            ("colors", slot) => ColorSlot::from_name(slot).map(Setting::Color),
+            _ => None,
+        }
+    }
+
+    /// The file section this setting lives in.
+    pub fn section(self) -> &'static str {
+        match self {
+            Setting::Theme | Setting::Wallpaper | Setting::Font | Setting::FontSize => "appearance",
+            Setting::KeyboardLayout
+            | Setting::DoubleClickMs
+            | Setting::StickyKeys
+            | Setting::SlowKeysMs => "input",
+            Setting::Language => "locale",
+            Setting::Color(_) => "colors",
+        }
+    }
+
+    /// The key within [`Setting::section`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::Theme => "theme",
+            Setting::Wallpaper => "wallpaper",
+            Setting::Font => "font",
+            Setting::FontSize => "font_size",
+            Setting::KeyboardLayout => "keyboard_layout",
+            Setting::DoubleClickMs => "double_click_ms",
+            Setting::StickyKeys => "sticky_keys",
+            Setting::SlowKeysMs => "slow_keys_ms",
+            Setting::Language => "language",
+            Setting::Color(slot) => slot.name(),
+        }
+    }
+
+    /// The full `section.key` name.
+    pub fn key(self) -> String {
+        format!("{}.{}", self.section(), self.name())
+    }
+
+    /// Numeric ID carried in change notifications.
+    pub fn id(self) -> u64 {
+        match self {
+            Setting::Theme => 0,
+            Setting::Wallpaper => 1,
+            Setting::Font => 2,
+            Setting::FontSize => 3,
+            Setting::KeyboardLayout => 4,
+            Setting::DoubleClickMs => 5,
+            Setting::StickyKeys => 6,
+            Setting::SlowKeysMs => 7,
+            Setting::Language => 8,
+            Setting::Color(slot) => COLOR_ID_BASE + slot.index() as u64,
+        }
+    }
+
+    /// Inverse of [`Setting::id`].
+    pub fn from_id(id: u64) -> Option<Self> {
+        if id >= COLOR_ID_BASE {
+            let index = usize::try_from(id - COLOR_ID_BASE).ok()?;
+            return ColorSlot::ALL.get(index).copied().map(Setting::Color);
+        }
+        Self::SCALARS.get(id as usize).copied()
+    }
+
+    /// Whether the value is written as a TOML integer.
+    fn is_integer(self) -> bool {
+        matches!(
+            self,
+            Setting::FontSize | Setting::DoubleClickMs | Setting::SlowKeysMs
+        )
+    }
+
+    /// Whether the value is written as a TOML boolean.
+    fn is_bool(self) -> bool {
+        matches!(self, Setting::StickyKeys)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// A line of the configuration file that could not be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number.
+    pub line: usize,
+    /// What was wrong with it.
+    pub reason: &'static str,
+}
+
+/// The desktop settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopConfig {
+    /// Base color scheme.
+    pub theme: ThemePreset,
+    /// Colors that replace the theme's, by slot.
+    pub color_overrides: BTreeMap<ColorSlot, ThemeColor>,
+    /// Desktop background image; `None` paints the theme gradient.
+    pub wallpaper: Option<String>,
+    /// Font family name.
+    pub font: String,
+    /// Font size in pixels.
+    pub font_size: u8,
+    /// Keyboard layout name.
+    pub keyboard_layout: String,
+    /// Longest gap between the clicks of a double-click, in milliseconds.
+    pub double_click_ms: u32,
+    /// Sticky keys on.
+    pub sticky_keys: bool,
+    /// Slow keys delay in milliseconds, 0 when off.
+    pub slow_keys_ms: u32,
+    /// Message catalog language, empty for the English source strings.
+    pub language: String,
+}
+
+impl Default for DesktopConfig {
+    fn default() -> Self {
+        Self {
+            theme: ThemePreset::Dark,
+            color_overrides: BTreeMap::new(),
+            wallpaper: None,
+            font: String::from("monospace"),
+            font_size: 16,
+            keyboard_layout: String::from("us"),
+            double_click_ms: 400,
+            sticky_keys: false,
+            slow_keys_ms: 0,
+            language: String::new(),
+        }
+    }
+}
+
+impl DesktopConfig {
+    /// The theme's palette with the overrides applied.
+    pub fn colors(&self) -> ThemeColors {
+        let mut colors = self.theme.colors();
+        for (&slot, &color) in &self.color_overrides {
+            colors.set(slot, color);
+        }
+        colors
+    }
+
+    /// A setting's value as text, the form [`DesktopConfig::set`] accepts.
+    /// Colors without an override report the theme's color.
+    pub fn get(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Theme => String::from(self.theme.name()),
+            Setting::Wallpaper => self.wallpaper.clone().unwrap_or_default(),
+            Setting::Font => self.font.clone(),
+            Setting::FontSize => self.font_size.to_string(),
+            Setting::KeyboardLayout => self.keyboard_layout.clone(),
+            Setting::DoubleClickMs => self.double_click_ms.to_string(),
+            Setting::StickyKeys => self.sticky_keys.to_string(),
+            Setting::SlowKeysMs => self.slow_keys_ms.to_string(),
+            Setting::Language => self.language.clone(),
+            Setting::Color(slot) => self.colors().get(slot).to_string(),
+        }
+    }
+
+    /// Change a setting from its text form.
+    pub fn set(&mut self, setting: Setting, value: &str) -> Result<(), KernelError> {
+        let invalid = |reason| KernelError::InvalidArgument {
+            name: setting.name(),
+            value: reason,
+        };
+        match setting {
+            Setting::Theme => {
+                self.theme =
+                    ThemePreset::from_name(value).ok_or_else(|| invalid("unknown theme"))?;
+            }
+            Setting::Wallpaper => {
+                self.wallpaper = match value {
+                    "" => None,
+                    path if path.starts_with('/') => Some(String::from(path)),
+                    _ => return Err(invalid("wallpaper must be an absolute path")),
+                };
+            }
+            Setting::Font => {
+                if value.is_empty() {
+                    return Err(invalid("font name is empty"));
+                }
+                self.font = String::from(value);
+            }
+            Setting::FontSize => {
+                self.font_size = parse_in_range(value, FONT_SIZE_RANGE, invalid)? as u8;
+            }
+            Setting::KeyboardLayout => {
+                if !KEYBOARD_LAYOUTS.contains(&value) {
+                    return Err(invalid("unknown keyboard layout"));
+                }
+                self.keyboard_layout = String::from(value);
+            }
+            Setting::DoubleClickMs => {
+                self.double_click_ms = parse_in_range(value, DOUBLE_CLICK_RANGE, invalid)?;
+            }
+            Setting::StickyKeys => {
+                self.sticky_keys = match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(invalid("expected true or false")),
+                };
+            }
+            Setting::SlowKeysMs => {
+                self.slow_keys_ms = parse_in_range(value, SLOW_KEYS_RANGE, invalid)?;
+            }
+            Setting::Language => {
+                if !i18n::is_valid_language(value) {
+                    return Err(invalid("not a locale name"));
+                }
+                self.language = String::from(value);
+            }
+            Setting::Color(slot) => {
+                if value.is_empty() {
+                    self.color_overrides.remove(&slot);
+                } else {
+                    let color =
+                        ThemeColor::parse_hex(value).ok_or_else(|| invalid("expected #RRGGBB"))?;
+                    self.color_overrides.insert(slot, color);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings whose values differ between `self` and `other`.
+    pub fn changed_settings(&self, other: &DesktopConfig) -> Vec<Setting> {
+        Setting::SCALARS
+            .iter()
+            .copied()
+            .chain(ColorSlot::ALL.iter().map(|&slot| Setting::Color(slot)))
+            .filter(|&setting| self.get(setting) != other.get(setting))
+            .collect()
+    }
+
+    /// Parse a configuration file. Missing keys keep their defaults.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut config = Self::default();
+        let mut section = "";
+        for (i, raw) in text.lines().enumerate() {
+            let error = |reason| ParseError {
+                line: i + 1,
+                reason,
+            };
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                section = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("unterminated section header"))?
+                    .trim();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value"))?;
+            let Some(setting) = Setting::from_parts(section, key.trim()) else {
+                continue;
+            };
+            let value = value.trim();
+            let text = if setting.is_integer() {
+                if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(error("expected an integer"));
+                }
+                String::from(value)
+            } else if setting.is_bool() {
+                if value != "true" && value != "false" {
+                    return Err(error("expected true or false"));
+                }
+                String::from(value)
+            } else {
+                parse_string(value).ok_or_else(|| error("expected a quoted string"))?
+            };
+            config.set(setting, &text).map_err(|e| match e {
+                KernelError::InvalidArgument { value, .. } => error(value),
+                _ => error("invalid value"),
+            })?;
+        }
+        Ok(config)
+    }
+
+    /// Serialize in the format [`DesktopConfig::parse`] reads.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::from("# VeridianOS desktop settings\n");
+        let mut section = "";
+        for setting in Setting::SCALARS {
+            if setting.section() != section {
+                section = setting.section();
+                let _ = write!(out, "\n[{}]\n", section);
+            }
+            let value = self.get(setting);
+            if setting.is_integer() || setting.is_bool() {
+                let _ = writeln!(out, "{} = {}", setting.name(), value);
+            } else {
+                let _ = writeln!(out, "{} = {}", setting.name(), quote_string(&value));
+            }
+        }
+        if !self.color_overrides.is_empty() {
+            out.push_str("\n[colors]\n");
+            for (slot, color) in &self.color_overrides {
+                let _ = writeln!(out, "{} = \"{}\"", slot.name(), color);
+            }
+        }
+        out
+    }
+}
+
+/// Parse a decimal integer and check it against `range`, reporting a bad
+/// value through `invalid`.
+fn parse_in_range(
+    value: &str,
+    range: core::ops::RangeInclusive<u32>,
+    invalid: impl Fn(&'static str) -> KernelError,
+) -> Result<u32, KernelError> {
+    let n: u32 = value.parse().map_err(|_| invalid("expected a number"))?;
+    if range.contains(&n) {
+        Ok(n)
+    } else {
+        Err(invalid("value out of range"))
+    }
+}
+
+/// Cut a `#` comment that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parse a TOML basic string (`"..."` with `\"`, `\\`, `\n`, `\t`
+/// escapes). Trailing text after the closing quote is rejected.
+fn parse_string(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return chars.as_str().trim().is_empty().then_some(out),
+            '\\' => out.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                't' => '\t',
+                _ => return None,
+            }),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// Quote `value` as a TOML basic string.
+fn quote_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Global configuration
+// ---------------------------------------------------------------------------
+
+/// The active configuration; `None` until first use.
+static CONFIG: Mutex<Option<DesktopConfig>> = Mutex::new(None);
+
+/// Bumped on every change.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Run `f` with the active configuration.
+pub fn with_config<R, F: FnOnce(&DesktopConfig) -> R>(f: F) -> R {
+    let mut guard = CONFIG.lock();
+    f(guard.get_or_insert_with(DesktopConfig::default))
+}
+
+/// The active theme colors.
+pub fn colors() -> ThemeColors {
+    with_config(DesktopConfig::colors)
+}
+
+/// The current value of one setting.
+pub fn get(setting: Setting) -> String {
+    with_config(|config| config.get(setting))
+}
+
+/// Hand the accessibility options to the keyboard driver.
+fn apply_access_options(config: &DesktopConfig) {
+    crate::drivers::keyboard::set_access_options(config.sticky_keys, config.slow_keys_ms);
+}
+
+/// Load the message catalog for `language`. A missing catalog is reported
+/// but the setting is kept, and the desktop shows English.
+fn apply_language(language: &str) {
+    if let Err(e) = crate::services::locale::set_language(language) {
+        crate::println!("[DESKTOP] No message catalog for {:?}: {:?}", language, e);
+    }
+}
+
+/// Change count; compare against a saved value to detect changes.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// Change a setting, save the file and notify subscribers.
+///
+/// Setting a value that is already in effect does nothing.
+pub fn set(setting: Setting, value: &str) -> Result<(), KernelError> {
+    let text = {
+        let mut guard = CONFIG.lock();
+        let config = guard.get_or_insert_with(DesktopConfig::default);
+        let mut updated = config.clone();
+        updated.set(setting, value)?;
+        if updated == *config {
+            return Ok(());
+        }
+        *config = updated;
+        if matches!(setting, Setting::StickyKeys | Setting::SlowKeysMs) {
+            apply_access_options(config);
+        }
+        config.to_toml()
+    };
+    let generation = GENERATION.fetch_add(1, Ordering::AcqRel) + 1;
+
+    // Before the root filesystem is up the change only lives in memory
+    if crate::fs::try_get_vfs().is_some() {
+        if let Err(e) = crate::fs::write_file(CONFIG_PATH, text.as_bytes()) {
+            crate::println!("[DESKTOP] Could not save {}: {:?}", CONFIG_PATH, e);
+        }
+    }
+    if setting == Setting::Language {
+        apply_language(value);
+    }
+    crate::services::settings_ipc::notify_changed(setting, generation);
+    Ok(())
+}
+
+/// (Re)load the configuration from [`CONFIG_PATH`].
+///
+/// A missing file leaves the current settings in place; a malformed one is
+/// reported and ignored. Changed settings are announced as if set one by
+/// one.
+pub fn load() -> Result<(), KernelError> {
+    if crate::fs::try_get_vfs().is_none() {
+        return Err(KernelError::InvalidState {
+            expected: "filesystem mounted",
+            actual: "no filesystem",
+        });
+    }
+    let data = crate::fs::read_file(CONFIG_PATH)?;
+    let text = core::str::from_utf8(&data).map_err(|_| KernelError::InvalidArgument {
+        name: "desktop.toml",
+        value: "not UTF-8",
+    })?;
+    let loaded = DesktopConfig::parse(text).map_err(|e| {
+        crate::println!("[DESKTOP] {}:{}: {}", CONFIG_PATH, e.line, e.reason);
+        KernelError::InvalidArgument {
+            name: "desktop.toml",
+            value: e.reason,
+        }
+    })?;
+
+    let changed = {
+        let mut guard = CONFIG.lock();
+        let config = guard.get_or_insert_with(DesktopConfig::default);
+        let changed = config.changed_settings(&loaded);
+        *config = loaded;
+        apply_access_options(config);
+        changed
+    };
+    if changed.contains(&Setting::Language) {
+        apply_language(&get(Setting::Language));
+    }
+    if !changed.is_empty() {
+        let generation = GENERATION.fetch_add(1, Ordering::AcqRel) + 1;
+        for setting in changed {
+            crate::services::settings_ipc::notify_changed(setting, generation);
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setting_names_and_ids() {
+        let all = Setting::SCALARS
+            .iter()
+            .copied()
+            .chain(ColorSlot::ALL.iter().map(|&slot| Setting::Color(slot)));
+        for setting in all {
+            assert_eq!(Setting::parse(&setting.key()), Some(setting));
+            assert_eq!(Setting::from_id(setting.id()), Some(setting));
+        }
+        assert_eq!(
+            Setting::parse("colors.accent"),
+            Some(Setting::Color(ColorSlot::Accent))
+        );
+        assert_eq!(Setting::parse("appearance.accent"), None);
+        assert_eq!(Setting::parse("theme"), None);
+        assert_eq!(Setting::from_id(9), None);
+        assert_eq!(Setting::from_id(COLOR_ID_BASE + 29), None);
+    }
+
+    #[test]
+    fn test_parse_full_file() {
+        let text = "\
+# comment line
+[appearance]
+theme = \"nord\"   # trailing comment
+wallpaper = \"/usr/share/wallpapers/a#b.png\"
+font_size = 18
+
+[colors]
+accent = \"#BF616A\"
+
+[input]
+keyboard_layout = \"de\"
+double_click_ms = 250
+sticky_keys = true
+slow_keys_ms = 300
+
+[locale]
+language = \"pt_BR.UTF-8\"
+
+[future]
+sparkles = true
+";
+        let config = DesktopConfig::parse(text).unwrap();
+        assert_eq!(config.theme, ThemePreset::Nord);
+        assert_eq!(
+            config.wallpaper.as_deref(),
+            Some("/usr/share/wallpapers/a#b.png")
+        );
+        assert_eq!(config.font, "monospace");
+        assert_eq!(config.font_size, 18);
+        assert_eq!(config.keyboard_layout, "de");
+        assert_eq!(config.double_click_ms, 250);
+        assert!(config.sticky_keys);
+        assert_eq!(config.slow_keys_ms, 300);
+        assert_eq!(config.language, "pt_BR.UTF-8");
+
+        let colors = config.colors();
+        assert_eq!(colors.accent, ThemeColor::from_rgb(0xBF, 0x61, 0x6A));
+        assert_eq!(
+            colors.window_background,
+            ThemeColors::nord().window_background
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |text| DesktopConfig::parse(text).unwrap_err();
+        assert_eq!(err("[appearance\n").reason, "unterminated section header");
+        assert_eq!(err("\n[appearance]\ntheme\n").line, 3);
+        assert_eq!(
+            err("[appearance]\ntheme = nord\n").reason,
+            "expected a quoted string"
+        );
+        assert_eq!(
+            err("[appearance]\ntheme = \"nord\" x\n").reason,
+            "expected a quoted string"
+        );
+        assert_eq!(
+            err("[appearance]\ntheme = \"plaid\"\n").reason,
+            "unknown theme"
+        );
+        assert_eq!(
+            err("[input]\ndouble_click_ms = \"400\"\n").reason,
+            "expected an integer"
+        );
+        assert_eq!(
+            err("[input]\ndouble_click_ms = 5\n").reason,
+            "value out of range"
+        );
+        assert_eq!(
+            err("[input]\nsticky_keys = 1\n").reason,
+            "expected true or false"
+        );
+        assert_eq!(
+            err("[colors]\naccent = \"red\"\n").reason,
+            "expected #RRGGBB"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut config = DesktopConfig::default();
+        config.set(Setting::Theme, "dracula").unwrap();
+        config
+            .set(Setting::Wallpaper, "/home/me/\"quoted\".qoi")
+            .unwrap();
+        config.set(Setting::Font, "Terminus").unwrap();
+        config.set(Setting::StickyKeys, "true").unwrap();
+        config
+            .set(Setting::Color(ColorSlot::TitlebarBackground), "#803B4252")
+            .unwrap();
+        let text = config.to_toml();
+        assert!(text.contains("[colors]\ntitlebar_background = \"#803B4252\"\n"));
+        assert_eq!(DesktopConfig::parse(&text).unwrap(), config);
+
+        // Defaults serialize without a [colors] section.
+        let text = DesktopConfig::default().to_toml();
+        assert!(!text.contains("[colors]"));
+        assert_eq!(
+            DesktopConfig::parse(&text).unwrap(),
+            DesktopConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_set_and_changed_settings() {
+        let base = DesktopConfig::default();
+        let mut config = base.clone();
+        assert_eq!(
+            config.set(Setting::Wallpaper, "relative.png"),
+            Err(KernelError::InvalidArgument {
+                name: "wallpaper",
+                value: "wallpaper must be an absolute path",
+            })
+        );
+        assert_eq!(
+            config.set(Setting::KeyboardLayout, "klingon"),
+            Err(KernelError::InvalidArgument {
+                name: "keyboard_layout",
+                value: "unknown keyboard layout",
+            })
+        );
+        assert_eq!(
+            config.set(Setting::FontSize, "40"),
+            Err(KernelError::InvalidArgument {
+                name: "font_size",
+                value: "value out of range",
+            })
+        );
+        assert_eq!(
+            config.set(Setting::Language, "../de"),
+            Err(KernelError::InvalidArgument {
+                name: "language",
+                value: "not a locale name",
+            })
+        );
+        assert_eq!(config, base);
+
+        config.set(Setting::Theme, "light").unwrap();
+        config
+            .set(Setting::Color(ColorSlot::Accent), "#010203")
+            .unwrap();
+        config.set(Setting::DoubleClickMs, "600").unwrap();
+        let changed = base.changed_settings(&config);
+        // Switching theme changes most slots, so only check membership.
+        assert!(changed.contains(&Setting::Theme));
+        assert!(changed.contains(&Setting::DoubleClickMs));
+        assert!(changed.contains(&Setting::Color(ColorSlot::Accent)));
+        assert!(!changed.contains(&Setting::Font));
+
+        // Clearing an override falls back to the theme color.
+        config.set(Setting::Color(ColorSlot::Accent), "").unwrap();
+        assert_eq!(
+            config.get(Setting::Color(ColorSlot::Accent)),
+            ThemeColors::light().accent.to_string()
+        );
+        config.set(Setting::Wallpaper, "").unwrap();
+        assert_eq!(config.wallpaper, None);
+    }
+}
@@ -60,7 +60,9 @@ pub use shortcuts::ShortcutManager;
 // Shortcuts
 pub use shortcuts::{KeyBinding, KeyCode, ModifierMask, ShortcutAction, ShortcutPriority};
 // Theme
-pub use theme::{IconTheme, StyleProperty, ThemeColor, ThemeColors, ThemeManager, ThemePreset};
+pub use theme::{
+    ColorSlot, IconTheme, StyleProperty, ThemeColor, ThemeColors, ThemeManager, ThemePreset,
+};
 
 // ============================================================================
 // Tests
@@ -69,7 +71,7 @@ pub use theme::{IconTheme, StyleProperty, ThemeColor, ThemeColors, ThemeManager,
 #[cfg(test)]
 mod tests {
     #[allow(unused_imports)]
-    use alloc::{string::String, vec, vec::Vec};
+    use alloc::{format, string::String, vec, vec::Vec};
 
     use super::{
         clipboard::{CLIPBOARD_HISTORY_MAX, CLIPBOARD_MAX_DATA_SIZE},
@@ -431,6 +433,45 @@ mod tests {
         assert_eq!(mgr.gtk_theme_name(), "Dracula");
     }
 
+    #[test]
+    fn test_theme_preset_names() {
+        for preset in ThemePreset::ALL {
+            assert_eq!(ThemePreset::from_name(preset.name()), Some(preset));
+        }
+        assert_eq!(
+            ThemePreset::from_name("Solarized_Dark"),
+            Some(ThemePreset::SolarizedDark)
+        );
+        assert_eq!(ThemePreset::from_name("gruvbox"), None);
+    }
+
+    #[test]
+    fn test_theme_color_hex() {
+        let c = ThemeColor::parse_hex("#88C0D0").unwrap();
+        assert_eq!(c, ThemeColor::from_rgb(0x88, 0xC0, 0xD0));
+        assert_eq!(format!("{}", c), "#88C0D0");
+        let translucent = ThemeColor::parse_hex("#80112233").unwrap();
+        assert_eq!(format!("{}", translucent), "#80112233");
+        assert_eq!(ThemeColor::parse_hex("88C0D0"), None);
+        assert_eq!(ThemeColor::parse_hex("#88C0D"), None);
+        assert_eq!(ThemeColor::parse_hex("#+8C0D0"), None);
+        assert_eq!(c.to_bgra(), [0xD0, 0xC0, 0x88, 0xFF]);
+    }
+
+    #[test]
+    fn test_color_slots() {
+        let mut colors = ThemeColors::nord();
+        assert_eq!(ColorSlot::ALL.len(), 29);
+        for (i, &slot) in ColorSlot::ALL.iter().enumerate() {
+            assert_eq!(slot.index(), i);
+            assert_eq!(ColorSlot::from_name(slot.name()), Some(slot));
+        }
+        assert_eq!(colors.get(ColorSlot::Accent), colors.accent);
+        colors.set(ColorSlot::PanelBackground, ThemeColor(0xFF12_3456));
+        assert_eq!(colors.panel_background, ThemeColor(0xFF12_3456));
+        assert_eq!(ColorSlot::from_name("nope"), None);
+    }
+
     // --- Font Rendering Tests ---
 
     #[test]
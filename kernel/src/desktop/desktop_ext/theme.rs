@@ -25,6 +25,64 @@ pub enum ThemePreset {
     Custom,
 }
 
+impl ThemePreset {
+    /// Every preset, in menu order.
+    pub const ALL: [ThemePreset; 7] = [
+        ThemePreset::Dark,
+        ThemePreset::Light,
+        ThemePreset::SolarizedDark,
+        ThemePreset::SolarizedLight,
+        ThemePreset::Nord,
+        ThemePreset::Dracula,
+        ThemePreset::Custom,
+    ];
+
+    /// Name used in configuration files, e.g. `solarized-dark`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemePreset::Light => "light",
+            ThemePreset::Dark => "dark",
+            ThemePreset::SolarizedDark => "solarized-dark",
+            ThemePreset::SolarizedLight => "solarized-light",
+            ThemePreset::Nord => "nord",
+            ThemePreset::Dracula => "dracula",
+            ThemePreset::Custom => "custom",
+        }
+    }
+
+    /// Parse a preset name (case-insensitive; `-` and `_` are optional).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut key = [0u8; 16];
+        let mut len = 0;
+        for b in name.bytes().filter(|&b| b != b'-' && b != b'_') {
+            *key.get_mut(len)? = b.to_ascii_lowercase();
+            len += 1;
+        }
+        match &key[..len] {
+            b"light" => Some(ThemePreset::Light),
+            b"dark" => Some(ThemePreset::Dark),
+            b"solarizeddark" => Some(ThemePreset::SolarizedDark),
+            b"solarizedlight" => Some(ThemePreset::SolarizedLight),
+            b"nord" => Some(ThemePreset::Nord),
+            b"dracula" => Some(ThemePreset::Dracula),
+            b"custom" => Some(ThemePreset::Custom),
+            _ => None,
+        }
+    }
+
+    /// The preset's palette; `Custom` starts from the dark theme.
+    pub const fn colors(self) -> ThemeColors {
+        match self {
+            ThemePreset::Light => ThemeColors::light(),
+            ThemePreset::Dark | ThemePreset::Custom => ThemeColors::dark(),
+            ThemePreset::SolarizedDark => ThemeColors::solarized_dark(),
+            ThemePreset::SolarizedLight => ThemeColors::solarized_light(),
+            ThemePreset::Nord => ThemeColors::nord(),
+            ThemePreset::Dracula => ThemeColors::dracula(),
+        }
+    }
+}
+
 /// ARGB color (alpha in high byte).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ThemeColor(pub u32);
@@ -79,6 +137,30 @@ impl ThemeColor {
         Self::from_argb(self.alpha(), r as u8, g as u8, b as u8)
     }
 
+    /// The color as `0x00RRGGBB`, the form the text drawing helpers take.
+    pub const fn rgb(self) -> u32 {
+        self.0 & 0x00FF_FFFF
+    }
+
+    /// The color as bytes of a BGRA pixel buffer.
+    pub const fn to_bgra(self) -> [u8; 4] {
+        [self.blue(), self.green(), self.red(), self.alpha()]
+    }
+
+    /// Parse `#RRGGBB` (opaque) or `#AARRGGBB`.
+    pub fn parse_hex(s: &str) -> Option<Self> {
+        let hex = s.strip_prefix('#')?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        match hex.len() {
+            6 => Some(Self(0xFF00_0000 | value)),
+            8 => Some(Self(value)),
+            _ => None,
+        }
+    }
+
     /// Lighten a color by a percentage (0-100).
     pub fn lighten(self, percent: u32) -> Self {
         let factor = percent;
@@ -357,6 +439,102 @@ impl ThemeColors {
     }
 }
 
+impl core::fmt::Display for ThemeColor {
+    /// Formats as `#RRGGBB`, or `#AARRGGBB` when not fully opaque.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.alpha() == 0xFF {
+            write!(f, "#{:06X}", self.rgb())
+        } else {
+            write!(f, "#{:08X}", self.0)
+        }
+    }
+}
+
+/// Declares [`ColorSlot`] with one variant per [`ThemeColors`] field, and
+/// slot-indexed accessors on `ThemeColors`.
+macro_rules! color_slots {
+    ($($field:ident => $variant:ident,)*) => {
+        /// A named color slot of [`ThemeColors`]. Configuration files refer
+        /// to a slot by its field name, e.g. `titlebar_background`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+        pub enum ColorSlot {
+            $($variant,)*
+        }
+
+        impl ColorSlot {
+            /// Every slot, in field order.
+            pub const ALL: &'static [ColorSlot] = &[$(ColorSlot::$variant,)*];
+
+            /// The slot's field name.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(ColorSlot::$variant => stringify!($field),)*
+                }
+            }
+        }
+
+        impl ThemeColors {
+            /// Read one slot.
+            pub fn get(&self, slot: ColorSlot) -> ThemeColor {
+                match slot {
+                    $(ColorSlot::$variant => self.$field,)*
+                }
+            }
+
+            /// Overwrite one slot.
+            pub fn set(&mut self, slot: ColorSlot, color: ThemeColor) {
+                match slot {
+                    $(ColorSlot::$variant => self.$field = color,)*
+                }
+            }
+        }
+    };
+}
+
+color_slots! {
+    window_background => WindowBackground,
+    window_foreground => WindowForeground,
+    window_border => WindowBorder,
+    window_border_focused => WindowBorderFocused,
+    titlebar_background => TitlebarBackground,
+    titlebar_foreground => TitlebarForeground,
+    titlebar_background_inactive => TitlebarBackgroundInactive,
+    titlebar_foreground_inactive => TitlebarForegroundInactive,
+    button_background => ButtonBackground,
+    button_foreground => ButtonForeground,
+    button_hover => ButtonHover,
+    button_pressed => ButtonPressed,
+    accent => Accent,
+    selection_background => SelectionBackground,
+    selection_foreground => SelectionForeground,
+    desktop_background => DesktopBackground,
+    panel_background => PanelBackground,
+    panel_foreground => PanelForeground,
+    text_primary => TextPrimary,
+    text_secondary => TextSecondary,
+    text_disabled => TextDisabled,
+    error => Error,
+    warning => Warning,
+    success => Success,
+    info => Info,
+    scrollbar_track => ScrollbarTrack,
+    scrollbar_thumb => ScrollbarThumb,
+    tooltip_background => TooltipBackground,
+    tooltip_foreground => TooltipForeground,
+}
+
+impl ColorSlot {
+    /// Look a slot up by its field name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|slot| slot.name() == name)
+    }
+
+    /// Position of the slot in [`ColorSlot::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
 /// GTK/Qt-style property key for theme mapping stubs.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StyleProperty {
@@ -420,15 +598,10 @@ impl ThemeManager {
     /// Switch to a named theme preset.
     pub fn set_theme(&mut self, preset: ThemePreset) {
         self.current_preset = preset;
-        self.colors = match preset {
-            ThemePreset::Light => ThemeColors::light(),
-            ThemePreset::Dark => ThemeColors::dark(),
-            ThemePreset::SolarizedDark => ThemeColors::solarized_dark(),
-            ThemePreset::SolarizedLight => ThemeColors::solarized_light(),
-            ThemePreset::Nord => ThemeColors::nord(),
-            ThemePreset::Dracula => ThemeColors::dracula(),
-            ThemePreset::Custom => self.colors, // Keep current
-        };
+        if preset != ThemePreset::Custom {
+            // Custom keeps the current colors
+            self.colors = preset.colors();
+        }
     }
 
     /// Get current theme colors.
@@ -0,0 +1,141 @@
+//! Plugins shipped with the editor.
+
+use alloc::format;
+
+use super::{EditorEvent, EditorPlugin, EventResult, PluginContext};
+
+/// Closes `(`, `[`, `{` and `"` as they are typed, and types over a closing
+/// character that is already under the cursor.
+pub struct AutoBrackets;
+
+impl AutoBrackets {
+    const PAIRS: &'static [(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"')];
+}
+
+impl EditorPlugin for AutoBrackets {
+    fn name(&self) -> &str {
+        "auto-brackets"
+    }
+
+    fn event(&mut self, event: EditorEvent, ctx: &mut PluginContext) -> EventResult {
+        let EditorEvent::Key(ch) = event else {
+            return EventResult::Continue;
+        };
+
+        // Overtype a closer the plugin (or the user) already inserted
+        if Self::PAIRS.iter().any(|&(_, close)| close == ch)
+            && ctx.byte_after_cursor() == Some(ch as u8)
+        {
+            let (line, col) = ctx.cursor();
+            ctx.set_cursor(line, col + 1);
+            return EventResult::Handled;
+        }
+
+        match Self::PAIRS.iter().find(|&&(open, _)| open == ch) {
+            Some(&(open, close)) => {
+                let (line, col) = ctx.cursor();
+                ctx.insert(&format!("{}{}", open, close));
+                ctx.set_cursor(line, col + 1);
+                EventResult::Handled
+            }
+            None => EventResult::Continue,
+        }
+    }
+}
+
+/// Strips trailing spaces and tabs from every line before saving, or on
+/// demand with Ctrl+T.
+pub struct TrimWhitespace;
+
+impl TrimWhitespace {
+    const CMD_TRIM: u32 = 1;
+
+    /// Trim all lines; returns how many changed.
+    fn trim(ctx: &mut PluginContext) -> usize {
+        let mut trimmed = 0;
+        for line in 0..ctx.line_count() {
+            let text = ctx.line(line);
+            let keep = text
+                .iter()
+                .rposition(|&b| b != b' ' && b != b'\t')
+                .map_or(0, |i| i + 1);
+            if keep < text.len() {
+                ctx.replace_line(line, &text[..keep]);
+                trimmed += 1;
+            }
+        }
+        trimmed
+    }
+}
+
+impl EditorPlugin for TrimWhitespace {
+    fn name(&self) -> &str {
+        "trim-whitespace"
+    }
+
+    fn load(&mut self, ctx: &mut PluginContext) {
+        ctx.bind_key('\x14', Self::CMD_TRIM); // Ctrl+T
+    }
+
+    fn event(&mut self, event: EditorEvent, ctx: &mut PluginContext) -> EventResult {
+        match event {
+            EditorEvent::BeforeSave => {
+                Self::trim(ctx);
+            }
+            EditorEvent::Command(Self::CMD_TRIM) => {
+                let n = Self::trim(ctx);
+                ctx.set_status(&format!("Trimmed trailing whitespace on {} line(s)", n));
+                return EventResult::Handled;
+            }
+            _ => {}
+        }
+        EventResult::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop::text_buffer::TextBuffer;
+
+    fn context(text: &str) -> PluginContext {
+        let mut buffer = TextBuffer::new();
+        buffer.append(text.as_bytes());
+        PluginContext::new(buffer, 0, 0)
+    }
+
+    #[test]
+    fn test_auto_brackets() {
+        let mut ctx = context("");
+        let mut plugin = AutoBrackets;
+        assert_eq!(
+            plugin.event(EditorEvent::Key('('), &mut ctx),
+            EventResult::Handled
+        );
+        assert_eq!(ctx.line(0), b"()");
+        assert_eq!(ctx.cursor(), (0, 1));
+        assert_eq!(
+            plugin.event(EditorEvent::Key('x'), &mut ctx),
+            EventResult::Continue
+        );
+        assert_eq!(
+            plugin.event(EditorEvent::Key(')'), &mut ctx),
+            EventResult::Handled
+        );
+        assert_eq!(ctx.line(0), b"()");
+        assert_eq!(ctx.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn test_trim_whitespace() {
+        let mut ctx = context("a  \n\t\nb\t c \nd");
+        ctx.set_cursor(0, 3);
+        let mut plugin = TrimWhitespace;
+        plugin.event(EditorEvent::BeforeSave, &mut ctx);
+        assert_eq!(ctx.line(0), b"a");
+        assert_eq!(ctx.line(1), b"");
+        assert_eq!(ctx.line(2), b"b\t c");
+        assert_eq!(ctx.line(3), b"d");
+        assert_eq!(ctx.cursor(), (0, 1));
+    }
+}
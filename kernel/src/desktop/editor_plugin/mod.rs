@@ -0,0 +1,386 @@
+//! Text editor plugins
+//!
+//! Plugins see the editor through a [`PluginContext`]: the document buffer,
+//! the cursor, a status message and key bindings. They receive
+//! [`EditorEvent`]s in load order; a plugin that returns
+//! [`EventResult::Handled`] for a key suppresses the editor's default
+//! handling and the remaining plugins.
+//!
+//! Native plugins implement [`EditorPlugin`] directly ([`bundled`]);
+//! sandboxed WebAssembly plugins are loaded from [`PLUGIN_DIR`] through
+//! [`wasm::WasmPlugin`].
+
+pub mod bundled;
+pub mod wasm;
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use super::text_buffer::TextBuffer;
+use crate::error::KernelError;
+
+/// Directory scanned for `.wasm` plugins when an editor opens.
+pub const PLUGIN_DIR: &str = "/usr/lib/editor/plugins";
+
+/// Most plugins loaded into one editor.
+const MAX_PLUGINS: usize = 32;
+
+/// Keys with built-in meanings that plugins may not rebind: Backspace, Tab,
+/// Enter, Ctrl+N, Ctrl+O and Ctrl+S.
+const RESERVED_KEYS: &[char] = &['\x08', '\t', '\n', '\r', '\x0E', '\x0F', '\x13'];
+
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
+
+/// Something the editor tells its plugins about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorEvent {
+    /// A character key, before the editor inserts it
+    Key(char),
+    /// A key bound with [`PluginContext::bind_key`] was pressed; carries
+    /// the plugin's command id
+    Command(u32),
+    /// The document is about to be written to disk
+    BeforeSave,
+    /// A file was loaded
+    Opened,
+}
+
+/// Whether a plugin consumed an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+    /// Let other plugins and the editor see the event
+    Continue,
+    /// Stop here; for keys, skip the editor's default handling
+    Handled,
+}
+
+// ---------------------------------------------------------------------------
+// Plugin Context
+// ---------------------------------------------------------------------------
+
+/// The editor state a plugin may read and change
+///
+/// Columns are byte offsets, as in [`TextBuffer`].
+#[derive(Default)]
+pub struct PluginContext {
+    pub(super) buffer: TextBuffer,
+    pub(super) cursor_line: usize,
+    pub(super) cursor_col: usize,
+    /// Set when a plugin changed the buffer
+    pub(super) modified: bool,
+    pub(super) status: Option<String>,
+    /// Bindings requested during the current call
+    bindings: Vec<(char, u32)>,
+}
+
+impl PluginContext {
+    /// Context over `buffer` with the cursor at (`line`, `col`).
+    pub fn new(buffer: TextBuffer, line: usize, col: usize) -> Self {
+        Self {
+            buffer,
+            cursor_line: line,
+            cursor_col: col,
+            ..Self::default()
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.buffer.line_count()
+    }
+
+    /// Bytes of `line`, excluding its newline.
+    pub fn line(&self, line: usize) -> Vec<u8> {
+        self.buffer.line(line)
+    }
+
+    /// Cursor position as (line, column).
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor_line, self.cursor_col)
+    }
+
+    /// Move the cursor, clamped to the document and to a character start.
+    pub fn set_cursor(&mut self, line: usize, col: usize) {
+        self.cursor_line = line.min(self.buffer.line_count() - 1);
+        self.cursor_col = self.buffer.char_start(self.cursor_line, col);
+    }
+
+    /// Byte just after the cursor, if any on this line.
+    pub fn byte_after_cursor(&self) -> Option<u8> {
+        self.buffer
+            .line_head(self.cursor_line, self.cursor_col + 1)
+            .get(self.cursor_col)
+            .copied()
+    }
+
+    /// Insert `text` at the cursor and move the cursor past it.
+    pub fn insert(&mut self, text: &str) {
+        let offset = self.buffer.offset(self.cursor_line, self.cursor_col);
+        self.buffer.insert(offset, text.as_bytes());
+        match text.rfind('\n') {
+            Some(nl) => {
+                self.cursor_line += text.matches('\n').count();
+                self.cursor_col = text.len() - nl - 1;
+            }
+            None => self.cursor_col += text.len(),
+        }
+        self.modified = true;
+    }
+
+    /// Remove `len` bytes starting at (`line`, `col`), which may span lines.
+    pub fn delete(&mut self, line: usize, col: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let offset = self.buffer.offset(line, col);
+        self.buffer.remove(offset, len);
+        self.modified = true;
+        self.set_cursor(self.cursor_line, self.cursor_col);
+    }
+
+    /// Replace the contents of `line` (without its newline).
+    pub fn replace_line(&mut self, line: usize, text: &[u8]) {
+        if line >= self.buffer.line_count() {
+            return;
+        }
+        let offset = self.buffer.offset(line, 0);
+        self.buffer.remove(offset, self.buffer.line_len(line));
+        self.buffer.insert(offset, text);
+        self.modified = true;
+        self.set_cursor(self.cursor_line, self.cursor_col);
+    }
+
+    /// Show `msg` in the editor's status bar.
+    pub fn set_status(&mut self, msg: &str) {
+        self.status = Some(msg.to_string());
+    }
+
+    /// Ask for `key` to send [`EditorEvent::Command`]`(id)` to this plugin.
+    /// Only honoured while the plugin is loading.
+    pub fn bind_key(&mut self, key: char, id: u32) {
+        self.bindings.push((key, id));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Plugin Trait
+// ---------------------------------------------------------------------------
+
+/// An editor plugin
+pub trait EditorPlugin: Send + Sync {
+    /// Unique name, shown in status messages and used to unload.
+    fn name(&self) -> &str;
+
+    /// Called once when loaded; the place to bind keys.
+    fn load(&mut self, _ctx: &mut PluginContext) {}
+
+    /// Handle an event.
+    fn event(&mut self, event: EditorEvent, ctx: &mut PluginContext) -> EventResult;
+
+    /// Called once before the plugin is dropped.
+    fn unload(&mut self, _ctx: &mut PluginContext) {}
+}
+
+// ---------------------------------------------------------------------------
+// Plugin Manager
+// ---------------------------------------------------------------------------
+
+struct Slot {
+    plugin: Box<dyn EditorPlugin>,
+    enabled: bool,
+}
+
+/// The plugins loaded into one editor, in load order
+#[derive(Default)]
+pub struct PluginManager {
+    slots: Vec<Slot>,
+    /// Key -> (plugin name, command id)
+    bindings: BTreeMap<char, (String, u32)>,
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load `plugin` and register the keys it binds. Keys that are reserved
+    /// or already bound are skipped and reported in the status bar.
+    pub fn load(
+        &mut self,
+        mut plugin: Box<dyn EditorPlugin>,
+        ctx: &mut PluginContext,
+    ) -> Result<(), KernelError> {
+        if self.slots.len() >= MAX_PLUGINS {
+            return Err(KernelError::ResourceExhausted {
+                resource: "editor plugins",
+            });
+        }
+        if self.find(plugin.name()).is_some() {
+            return Err(KernelError::AlreadyExists {
+                resource: "editor plugin",
+                id: 0,
+            });
+        }
+
+        ctx.bindings.clear();
+        plugin.load(ctx);
+        let name = plugin.name().to_string();
+        for (key, id) in core::mem::take(&mut ctx.bindings) {
+            if RESERVED_KEYS.contains(&key) || self.bindings.contains_key(&key) {
+                ctx.set_status(&format!("{}: key {:?} is not available", name, key));
+                continue;
+            }
+            self.bindings.insert(key, (name.clone(), id));
+        }
+        self.slots.push(Slot {
+            plugin,
+            enabled: true,
+        });
+        Ok(())
+    }
+
+    /// Unload plugin `name`, releasing its key bindings.
+    pub fn unload(&mut self, name: &str, ctx: &mut PluginContext) -> Result<(), KernelError> {
+        let idx = self.find(name).ok_or(KernelError::NotFound {
+            resource: "editor plugin",
+            id: 0,
+        })?;
+        let mut slot = self.slots.remove(idx);
+        slot.plugin.unload(ctx);
+        ctx.bindings.clear();
+        self.bindings.retain(|_, (owner, _)| owner != name);
+        Ok(())
+    }
+
+    /// Enable or disable plugin `name` without unloading it.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), KernelError> {
+        let idx = self.find(name).ok_or(KernelError::NotFound {
+            resource: "editor plugin",
+            id: 0,
+        })?;
+        self.slots[idx].enabled = enabled;
+        Ok(())
+    }
+
+    /// Loaded plugins as (name, enabled).
+    pub fn plugins(&self) -> Vec<(String, bool)> {
+        self.slots
+            .iter()
+            .map(|s| (s.plugin.name().to_string(), s.enabled))
+            .collect()
+    }
+
+    /// Deliver `event` to each enabled plugin until one handles it.
+    pub fn dispatch(&mut self, event: EditorEvent, ctx: &mut PluginContext) -> EventResult {
+        for slot in self.slots.iter_mut().filter(|s| s.enabled) {
+            if slot.plugin.event(event, ctx) == EventResult::Handled {
+                return EventResult::Handled;
+            }
+        }
+        EventResult::Continue
+    }
+
+    /// Deliver a key press: a bound key becomes a command for its plugin,
+    /// anything else an [`EditorEvent::Key`] for all plugins. Reserved keys
+    /// always go to the editor.
+    pub fn dispatch_key(&mut self, key: char, ctx: &mut PluginContext) -> EventResult {
+        if RESERVED_KEYS.contains(&key) {
+            return EventResult::Continue;
+        }
+        let Some((owner, id)) = self.bindings.get(&key) else {
+            return self.dispatch(EditorEvent::Key(key), ctx);
+        };
+        let id = *id;
+        match self.find(owner) {
+            Some(idx) if self.slots[idx].enabled => {
+                self.slots[idx].plugin.event(EditorEvent::Command(id), ctx);
+                EventResult::Handled
+            }
+            _ => EventResult::Continue,
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|s| s.plugin.name() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(text: &str) -> PluginContext {
+        let mut buffer = TextBuffer::new();
+        buffer.append(text.as_bytes());
+        PluginContext::new(buffer, 0, 0)
+    }
+
+    struct Counter {
+        keys: usize,
+    }
+
+    impl EditorPlugin for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+        fn load(&mut self, ctx: &mut PluginContext) {
+            ctx.bind_key('\x01', 7);
+            ctx.bind_key('\x13', 8); // Ctrl+S is reserved
+        }
+        fn event(&mut self, event: EditorEvent, ctx: &mut PluginContext) -> EventResult {
+            match event {
+                EditorEvent::Key(_) => self.keys += 1,
+                EditorEvent::Command(id) => ctx.set_status(&format!("command {}", id)),
+                _ => {}
+            }
+            EventResult::Continue
+        }
+    }
+
+    #[test]
+    fn test_context_insert_and_delete() {
+        let mut ctx = context("ab\ncd");
+        ctx.set_cursor(0, 1);
+        ctx.insert("x\ny");
+        assert_eq!(ctx.line(0), b"ax");
+        assert_eq!(ctx.line(1), b"yb");
+        assert_eq!(ctx.cursor(), (1, 1));
+        ctx.delete(0, 2, 1);
+        assert_eq!(ctx.line(0), b"axyb");
+        assert_eq!(ctx.line_count(), 2);
+        assert!(ctx.modified);
+    }
+
+    #[test]
+    fn test_manager_bindings_and_lifecycle() {
+        let mut ctx = context("");
+        let mut plugins = PluginManager::new();
+        plugins
+            .load(Box::new(Counter { keys: 0 }), &mut ctx)
+            .unwrap();
+        assert!(ctx.status.is_some(), "reserved key should be reported");
+        assert!(plugins
+            .load(Box::new(Counter { keys: 0 }), &mut ctx)
+            .is_err());
+
+        assert_eq!(plugins.dispatch_key('\x01', &mut ctx), EventResult::Handled);
+        assert_eq!(ctx.status.as_deref(), Some("command 7"));
+        assert_eq!(plugins.dispatch_key('a', &mut ctx), EventResult::Continue);
+
+        plugins.set_enabled("counter", false).unwrap();
+        assert_eq!(
+            plugins.dispatch_key('\x01', &mut ctx),
+            EventResult::Continue
+        );
+
+        plugins.unload("counter", &mut ctx).unwrap();
+        assert!(plugins.plugins().is_empty());
+        assert!(plugins.bindings.is_empty());
+    }
+}
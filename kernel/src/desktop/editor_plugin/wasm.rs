@@ -0,0 +1,250 @@
+//! WebAssembly editor plugins
+//!
+//! A plugin is a module importing only from the `editor` module below and
+//! exporting any of these hooks (all optional):
+//!
+//! - `load()`, once when loaded, and `unload()`, once when unloaded;
+//! - `on_key(ch: i32) -> i32` for [`EditorEvent::Key`];
+//! - `on_command(id: i32) -> i32` for [`EditorEvent::Command`];
+//! - `before_save()` and `opened()`.
+//!
+//! `on_key` and `on_command` return 1 for [`EventResult::Handled`].
+//!
+//! Imports (`editor` module, all i32): `line_count() -> n`,
+//! `line_len(line) -> len`, `read_line(line, ptr, cap) -> len`,
+//! `cursor_line() -> line`, `cursor_col() -> col`, `set_cursor(line, col)`,
+//! `insert(ptr, len) -> 0`, `delete(line, col, len)`, `status(ptr, len)`
+//! and `bind_key(key, id)`. Calls that get bad arguments return -1.
+//!
+//! Each hook runs with a fresh fuel budget. A plugin that traps is
+//! reported in the status bar and receives no further events.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use super::{EditorEvent, EditorPlugin, EventResult, PluginContext};
+use crate::{
+    error::KernelError,
+    wasm::{Config, FuncType, Host, Instance, Memory, Module, Trap, ValType, Value},
+};
+
+/// Import module name
+const MODULE: &str = "editor";
+
+/// Instructions per hook call
+const FUEL: u64 = 10_000_000;
+
+/// Linear memory limit in pages (1 MiB)
+const MAX_PAGES: u32 = 16;
+
+/// Imports: name, parameter count (all i32) and whether an i32 is returned
+const FUNCS: &[(&str, usize, bool)] = &[
+    ("line_count", 0, true),
+    ("line_len", 1, true),
+    ("read_line", 3, true),
+    ("cursor_line", 0, true),
+    ("cursor_col", 0, true),
+    ("set_cursor", 2, false),
+    ("insert", 2, true),
+    ("delete", 3, false),
+    ("status", 2, false),
+    ("bind_key", 2, false),
+];
+
+/// Host side of the plugin ABI. Holds the editor context for the duration
+/// of one hook call.
+#[derive(Default)]
+struct EditorHost {
+    ctx: Option<PluginContext>,
+}
+
+impl Host for EditorHost {
+    fn resolve(&mut self, module: &str, name: &str, ty: &FuncType) -> Option<usize> {
+        if module != MODULE {
+            return None;
+        }
+        let id = FUNCS.iter().position(|(n, _, _)| *n == name)?;
+        let (_, params, has_result) = FUNCS[id];
+        let results_ok = if has_result {
+            ty.results == [ValType::I32]
+        } else {
+            ty.results.is_empty()
+        };
+        (results_ok && ty.params.len() == params && ty.params.iter().all(|&t| t == ValType::I32))
+            .then_some(id)
+    }
+
+    fn call(&mut self, id: usize, a: &[u64], mem: &mut Memory) -> Result<Option<u64>, Trap> {
+        let ctx = self
+            .ctx
+            .as_mut()
+            .ok_or(Trap::Host("editor call outside a hook"))?;
+        let arg = |i: usize| a[i] as u32;
+        let result: i32 = match FUNCS[id].0 {
+            "line_count" => ctx.line_count() as i32,
+            "line_len" => {
+                if arg(0) as usize >= ctx.line_count() {
+                    -1
+                } else {
+                    ctx.buffer.line_len(arg(0) as usize) as i32
+                }
+            }
+            "read_line" => {
+                if arg(0) as usize >= ctx.line_count() {
+                    -1
+                } else {
+                    let line = ctx.buffer.line_head(arg(0) as usize, arg(2) as usize);
+                    mem.bytes_mut(arg(1), line.len() as u32)?
+                        .copy_from_slice(&line);
+                    line.len() as i32
+                }
+            }
+            "cursor_line" => ctx.cursor().0 as i32,
+            "cursor_col" => ctx.cursor().1 as i32,
+            "set_cursor" => {
+                ctx.set_cursor(arg(0) as usize, arg(1) as usize);
+                0
+            }
+            "insert" => match core::str::from_utf8(mem.bytes(arg(0), arg(1))?) {
+                Ok(text) => {
+                    ctx.insert(text);
+                    0
+                }
+                Err(_) => -1,
+            },
+            "delete" => {
+                ctx.delete(arg(0) as usize, arg(1) as usize, arg(2) as usize);
+                0
+            }
+            "status" => {
+                let msg = String::from_utf8_lossy(mem.bytes(arg(0), arg(1).min(256))?);
+                ctx.set_status(&msg);
+                0
+            }
+            "bind_key" => {
+                if let Some(key) = char::from_u32(arg(0)) {
+                    ctx.bind_key(key, arg(1));
+                }
+                0
+            }
+            _ => -1,
+        };
+        Ok(FUNCS[id].2.then_some(result as u32 as u64))
+    }
+}
+
+/// An editor plugin running in the WebAssembly sandbox
+pub struct WasmPlugin {
+    name: String,
+    instance: Instance<EditorHost>,
+    faulted: bool,
+}
+
+impl WasmPlugin {
+    /// Instantiate plugin `name` from a module binary.
+    pub fn new(name: &str, bytes: &[u8]) -> Result<Self, KernelError> {
+        let invalid = |_| KernelError::InvalidArgument {
+            name: "editor plugin",
+            value: "invalid module",
+        };
+        let module = Module::decode(bytes).map_err(invalid)?;
+        let config = Config {
+            max_pages: MAX_PAGES,
+            fuel: Some(FUEL),
+            ..Config::default()
+        };
+        let instance = Instance::new(module, EditorHost::default(), config).map_err(|e| {
+            crate::println!("[EDITOR] plugin {}: {}", name, e);
+            KernelError::InvalidArgument {
+                name: "editor plugin",
+                value: "failed to instantiate",
+            }
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            instance,
+            faulted: false,
+        })
+    }
+
+    /// Load a plugin from a `.wasm` file, named after the file.
+    pub fn from_file(path: &str) -> Result<Self, KernelError> {
+        let bytes = crate::fs::read_file(path)?;
+        let file = path.rsplit('/').next().unwrap_or(path);
+        Self::new(file.strip_suffix(".wasm").unwrap_or(file), &bytes)
+    }
+
+    /// Run hook `export` if the module has it. Returns its i32 result.
+    fn hook(&mut self, export: &str, args: &[Value], ctx: &mut PluginContext) -> Option<i32> {
+        if self.faulted || self.instance.module().export(export).is_none() {
+            return None;
+        }
+        self.instance.refuel();
+        self.instance.host_mut().ctx = Some(core::mem::take(ctx));
+        let result = self.instance.invoke(export, args);
+        *ctx = self.instance.host_mut().ctx.take().unwrap_or_default();
+        match result {
+            Ok(values) => match values.first() {
+                Some(Value::I32(v)) => Some(*v),
+                _ => Some(0),
+            },
+            Err(e) => {
+                self.faulted = true;
+                ctx.set_status(&format!("plugin {} disabled: {}", self.name, e));
+                None
+            }
+        }
+    }
+}
+
+impl EditorPlugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&mut self, ctx: &mut PluginContext) {
+        self.hook("load", &[], ctx);
+    }
+
+    fn event(&mut self, event: EditorEvent, ctx: &mut PluginContext) -> EventResult {
+        let handled = match event {
+            EditorEvent::Key(ch) => self.hook("on_key", &[Value::I32(ch as i32)], ctx),
+            EditorEvent::Command(id) => self.hook("on_command", &[Value::I32(id as i32)], ctx),
+            EditorEvent::BeforeSave => self.hook("before_save", &[], ctx),
+            EditorEvent::Opened => self.hook("opened", &[], ctx),
+        };
+        if handled == Some(1) {
+            EventResult::Handled
+        } else {
+            EventResult::Continue
+        }
+    }
+
+    fn unload(&mut self, ctx: &mut PluginContext) {
+        self.hook("unload", &[], ctx);
+    }
+}
+
+/// Load every `.wasm` file in `dir`; failures are logged and skipped.
+pub fn scan_dir(dir: &str) -> Vec<WasmPlugin> {
+    let Ok(entries) = crate::fs::get_vfs()
+        .read()
+        .resolve_path(dir)
+        .and_then(|node| node.readdir())
+    else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter(|e| e.name.ends_with(".wasm"))
+        .filter_map(|e| {
+            let path = format!("{}/{}", dir, e.name);
+            WasmPlugin::from_file(&path)
+                .map_err(|err| crate::println!("[EDITOR] skipping {}: {:?}", path, err))
+                .ok()
+        })
+        .collect()
+}
@@ -2,15 +2,20 @@
 //!
 //! Provides graphical file browsing and management using the window manager and
 //! VFS.
+//!
+//! Listing a directory only reads its entries. File sizes and recursive
+//! directory sizes are filled in afterwards by
+//! [`FileManager::poll_background`], which does a bounded amount of filesystem
+//! work per frame so a huge directory never stalls the UI.
 
-use alloc::{format, string::String, vec, vec::Vec};
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
 
 use spin::RwLock;
 
 use crate::{
     desktop::window_manager::{with_window_manager, InputEvent, WindowId},
     error::KernelError,
-    fs::{get_vfs, NodeType},
+    fs::{get_vfs, DirEntry, NodeType, VfsNode},
     sync::once_lock::GlobalState,
 };
 
@@ -19,12 +24,98 @@ use crate::{
 struct FileEntry {
     name: String,
     node_type: NodeType,
-    #[allow(dead_code)] // Set during directory scan; displayed when file details view is added
+    /// Size in bytes (recursive total for directories), valid once
+    /// `size_known` is set
     size: usize,
+    size_known: bool,
     #[allow(dead_code)] // Set via UI interaction; used in multi-select operations (future)
     selected: bool,
 }
 
+/// Filesystem nodes visited per [`FileManager::poll_background`] call.
+const SIZE_SCAN_BUDGET: usize = 256;
+
+/// Bytes of a file read for MIME sniffing when opening it.
+const MIME_SNIFF_LEN: usize = 512;
+
+/// Incremental recursive size computation for one directory entry.
+struct SizeScan {
+    /// Index into `FileManager::entries` being sized
+    entry: usize,
+    /// Directories still to be listed
+    pending: Vec<Arc<dyn VfsNode>>,
+    /// Directory whose listing is being consumed
+    current: Option<Arc<dyn VfsNode>>,
+    listing: Vec<DirEntry>,
+    pos: usize,
+    /// Bytes counted so far
+    total: usize,
+}
+
+impl SizeScan {
+    fn new(entry: usize, root: Arc<dyn VfsNode>) -> Self {
+        Self {
+            entry,
+            pending: vec![root],
+            current: None,
+            listing: Vec::new(),
+            pos: 0,
+            total: 0,
+        }
+    }
+
+    /// Visit nodes until `budget` is used up. Returns `true` when the whole
+    /// subtree has been counted.
+    fn step(&mut self, budget: &mut usize) -> bool {
+        while *budget > 0 {
+            *budget -= 1;
+            if self.pos >= self.listing.len() {
+                let Some(dir) = self.pending.pop() else {
+                    return true;
+                };
+                self.listing = dir.readdir().unwrap_or_default();
+                self.pos = 0;
+                self.current = Some(dir);
+                continue;
+            }
+
+            let entry = &self.listing[self.pos];
+            self.pos += 1;
+            if entry.name == "." || entry.name == ".." {
+                continue;
+            }
+            let Some(Ok(child)) = self.current.as_ref().map(|dir| dir.lookup(&entry.name)) else {
+                continue;
+            };
+            match entry.node_type {
+                NodeType::Directory => self.pending.push(child),
+                NodeType::File => self.total += child.metadata().map(|m| m.size).unwrap_or(0),
+                // Symlinks are not followed, so cycles cannot occur
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+/// Format a byte count as e.g. `512B`, `1.5K`, `23M`.
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes;
+    let mut tenths = 0;
+    let mut unit = 0;
+    while value >= 1024 && unit < UNITS.len() - 1 {
+        tenths = (value % 1024) * 10 / 1024;
+        value /= 1024;
+        unit += 1;
+    }
+    if unit > 0 && value < 10 {
+        format!("{}.{}{}", value, tenths, UNITS[unit])
+    } else {
+        format!("{}{}", value, UNITS[unit])
+    }
+}
+
 /// File manager state
 pub struct FileManager {
     /// Window ID
@@ -43,6 +134,13 @@ pub struct FileManager {
     /// File entries in current directory
     entries: Vec<FileEntry>,
 
+    /// Node of the current directory, for lazy size lookups
+    dir_node: Option<Arc<dyn VfsNode>>,
+    /// Entries before this index have been sized or are being scanned
+    next_unsized: usize,
+    /// Directory size computation in progress
+    size_scan: Option<SizeScan>,
+
     /// Selected entry index
     selected_index: usize,
 
@@ -80,6 +178,9 @@ impl FileManager {
             pool_buf_id,
             current_path: String::from("/"),
             entries: Vec::new(),
+            dir_node: None,
+            next_unsized: 0,
+            size_scan: None,
             selected_index: 0,
             scroll_offset: 0,
             width,
@@ -99,6 +200,9 @@ impl FileManager {
         println!("[FILE-MANAGER] Refreshing directory: {}", self.current_path);
 
         self.entries.clear();
+        self.dir_node = None;
+        self.next_unsized = 0;
+        self.size_scan = None;
 
         // Get VFS
         let vfs = get_vfs();
@@ -113,13 +217,16 @@ impl FileManager {
                 match dir_node.readdir() {
                     Ok(entries) => {
                         for entry in entries {
+                            // Sizes are filled in lazily by poll_background()
                             self.entries.push(FileEntry {
                                 name: entry.name,
                                 node_type: entry.node_type,
-                                size: 0, // Size not available in DirEntry
+                                size: 0,
+                                size_known: false,
                                 selected: false,
                             });
                         }
+                        self.dir_node = Some(dir_node);
                     }
                     Err(_) => {
                         println!("[FILE-MANAGER] Failed to read directory");
@@ -147,6 +254,7 @@ impl FileManager {
                     name: String::from(".."),
                     node_type: NodeType::Directory,
                     size: 0,
+                    size_known: true,
                     selected: false,
                 },
             );
@@ -157,6 +265,57 @@ impl FileManager {
         Ok(())
     }
 
+    /// Advance lazy size computation by a bounded amount of work.
+    ///
+    /// Looks up file sizes and sums directory subtrees, visiting at most
+    /// [`SIZE_SCAN_BUDGET`] filesystem nodes per call. Called once per frame;
+    /// returns `true` if any entry's size became known.
+    pub fn poll_background(&mut self) -> bool {
+        let Some(dir) = self.dir_node.clone() else {
+            return false;
+        };
+        let mut budget = SIZE_SCAN_BUDGET;
+        let mut changed = false;
+
+        while budget > 0 {
+            if let Some(scan) = self.size_scan.as_mut() {
+                if scan.step(&mut budget) {
+                    if let Some(entry) = self.entries.get_mut(scan.entry) {
+                        entry.size = scan.total;
+                        entry.size_known = true;
+                    }
+                    self.size_scan = None;
+                    changed = true;
+                }
+                continue;
+            }
+
+            let Some(index) =
+                (self.next_unsized..self.entries.len()).find(|&i| !self.entries[i].size_known)
+            else {
+                break;
+            };
+            self.next_unsized = index + 1;
+            budget -= 1;
+
+            let node_type = self.entries[index].node_type;
+            match dir.lookup(&self.entries[index].name) {
+                Ok(node) if node_type == NodeType::Directory => {
+                    self.size_scan = Some(SizeScan::new(index, node));
+                }
+                Ok(node) => {
+                    let entry = &mut self.entries[index];
+                    entry.size = node.metadata().map(|m| m.size).unwrap_or(0);
+                    entry.size_known = true;
+                    changed = true;
+                }
+                Err(_) => self.entries[index].size_known = true,
+            }
+        }
+
+        changed
+    }
+
     /// Process input event
     pub fn process_input(&mut self, event: InputEvent) -> Result<(), KernelError> {
         match event {
@@ -280,11 +439,18 @@ impl FileManager {
                     format!("{}/{}", self.current_path, entry.name)
                 };
 
-                // Read file header bytes for magic-based MIME detection
-                let header_bytes = crate::fs::read_file(&file_path).ok().map(|data| {
-                    let len = core::cmp::min(data.len(), 512);
-                    data[..len].to_vec()
-                });
+                // Read just the header bytes for magic-based MIME detection
+                // (never the whole file, which may be huge)
+                let header_bytes = get_vfs()
+                    .read()
+                    .open(&file_path, crate::fs::file::OpenFlags::read_only())
+                    .and_then(|node| {
+                        let mut header = vec![0u8; MIME_SNIFF_LEN];
+                        let len = node.read(0, &mut header)?;
+                        header.truncate(len);
+                        Ok(header)
+                    })
+                    .ok();
 
                 // Detect MIME type via extension + magic bytes
                 let mime = crate::desktop::mime::MimeDatabase::detect_mime(
@@ -377,29 +543,27 @@ impl FileManager {
     pub fn render(&self, buf: &mut [u8], width: usize, _height: usize) -> Result<(), KernelError> {
         use super::renderer::draw_char_into_buffer;
 
-        // Clear to dark gray background (BGRA: 0x2A2A2A)
+        let colors = crate::desktop::config::colors();
+
+        // Clear to the theme's window background
+        let background = colors.window_background.to_bgra();
         for chunk in buf.chunks_exact_mut(4) {
-            chunk[0] = 0x2A; // B
-            chunk[1] = 0x2A; // G
-            chunk[2] = 0x2A; // R
-            chunk[3] = 0xFF; // A
+            chunk.copy_from_slice(&background);
         }
 
         // Draw header: current path
         let header = self.current_path.as_bytes();
         let prefix = b"Path: ";
         for (i, &ch) in prefix.iter().chain(header.iter()).enumerate() {
-            draw_char_into_buffer(buf, width, ch, 8 + i * 8, 6, 0xDDDDDD);
+            draw_char_into_buffer(buf, width, ch, 8 + i * 8, 6, colors.text_primary.rgb());
         }
 
         // Draw separator line at y=24
+        let separator = colors.window_border.to_bgra();
         for x in 0..width {
             let offset = (24 * width + x) * 4;
             if offset + 3 < buf.len() {
-                buf[offset] = 0x55;
-                buf[offset + 1] = 0x55;
-                buf[offset + 2] = 0x55;
-                buf[offset + 3] = 0xFF;
+                buf[offset..offset + 4].copy_from_slice(&separator);
             }
         }
 
@@ -412,15 +576,14 @@ impl FileManager {
             let y = start_y + row * line_height;
 
             // Highlight selected row
-            if i == self.selected_index {
+            let selected = i == self.selected_index;
+            if selected {
+                let highlight = colors.selection_background.to_bgra();
                 for dy in 0..line_height {
                     for x in 0..width {
                         let offset = ((y + dy) * width + x) * 4;
                         if offset + 3 < buf.len() {
-                            buf[offset] = 0x50;
-                            buf[offset + 1] = 0x40;
-                            buf[offset + 2] = 0x30;
-                            buf[offset + 3] = 0xFF;
+                            buf[offset..offset + 4].copy_from_slice(&highlight);
                         }
                     }
                 }
@@ -434,8 +597,12 @@ impl FileManager {
             };
 
             let (text_color, prefix_color) = match entry.node_type {
-                NodeType::Directory => (0x55AAFF_u32, 0x55AAFF_u32),
-                _ => (0xCCCCCC, 0x888888),
+                _ if selected => (
+                    colors.selection_foreground.rgb(),
+                    colors.selection_foreground.rgb(),
+                ),
+                NodeType::Directory => (colors.accent.rgb(), colors.accent.rgb()),
+                _ => (colors.text_primary.rgb(), colors.text_secondary.rgb()),
             };
 
             // Draw prefix
@@ -448,6 +615,26 @@ impl FileManager {
             for (j, &ch) in entry.name.as_bytes().iter().enumerate() {
                 draw_char_into_buffer(buf, width, ch, name_x + j * 8, y + 1, text_color);
             }
+
+            // Draw size, right-aligned; "..." while still being computed
+            let size = if entry.size_known {
+                if entry.name == ".." {
+                    String::new()
+                } else {
+                    format_size(entry.size)
+                }
+            } else {
+                String::from("...")
+            };
+            let size_x = width.saturating_sub(8 + size.len() * 8);
+            let size_color = if selected {
+                colors.selection_foreground.rgb()
+            } else {
+                colors.text_secondary.rgb()
+            };
+            for (j, &ch) in size.as_bytes().iter().enumerate() {
+                draw_char_into_buffer(buf, width, ch, size_x + j * 8, y + 1, size_color);
+            }
         }
 
         Ok(())
@@ -528,10 +715,20 @@ mod tests {
             name: String::from("test.txt"),
             node_type: NodeType::File,
             size: 1024,
+            size_known: true,
             selected: false,
         };
 
         assert_eq!(entry.name, "test.txt");
         assert_eq!(entry.size, 1024);
     }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(1536), "1.5K");
+        assert_eq!(format_size(20 * 1024 * 1024), "20M");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0G");
+    }
 }
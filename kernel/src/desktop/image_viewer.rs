@@ -1,11 +1,14 @@
 //! Image Viewer
 //!
-//! Displays PPM (P3/P6) and BMP images with scaling support.
+//! Displays PNG, BMP, QOI, PPM (P3/P6) and TGA images with scaling support.
+//! PNG, BMP and QOI are decoded by the shared `image-format` crate.
 
 #![allow(dead_code)]
 
 use alloc::{string::String, vec, vec::Vec};
 
+use image_format::ImageError;
+
 use super::renderer::draw_string_into_buffer;
 
 // ---------------------------------------------------------------------------
@@ -15,6 +18,7 @@ use super::renderer::draw_string_into_buffer;
 /// Supported image formats.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageFormat {
+    Png,
     Ppm,
     Bmp,
     Tga,
@@ -110,15 +114,15 @@ impl ImageViewer {
     // -----------------------------------------------------------------------
 
     /// Load a PPM image (P3 ASCII or P6 binary).
-    pub fn load_ppm(data: &[u8]) -> Result<Image, &'static str> {
+    pub fn load_ppm(data: &[u8]) -> Result<Image, ImageError> {
         if data.len() < 3 {
-            return Err("ppm: data too short");
+            return Err(ImageError::TruncatedData);
         }
 
         let is_p6 = data.starts_with(b"P6");
         let is_p3 = data.starts_with(b"P3");
         if !is_p3 && !is_p6 {
-            return Err("ppm: unsupported magic (expected P3 or P6)");
+            return Err(ImageError::InvalidSignature);
         }
 
         // Find the header portion (width, height, maxval).
@@ -149,20 +153,20 @@ impl ImageViewer {
                 pos += 1;
             }
             if pos > start {
-                let num = parse_ascii_usize(&data[start..pos])?;
+                let num = parse_ascii_usize(&data[start..pos]).ok_or(ImageError::CorruptData)?;
                 tokens.push(num);
             }
         }
 
         if tokens.len() < 3 {
-            return Err("ppm: incomplete header");
+            return Err(ImageError::TruncatedData);
         }
 
         let width = tokens[0];
         let height = tokens[1];
         let max_val = tokens[2];
         if width == 0 || height == 0 || max_val == 0 {
-            return Err("ppm: zero dimension or maxval");
+            return Err(ImageError::InvalidDimensions);
         }
 
         let pixel_count = width * height;
@@ -176,7 +180,7 @@ impl ImageViewer {
             let bpp = if max_val > 255 { 6 } else { 3 };
             let needed = pixel_count * bpp;
             if pos + needed > data.len() {
-                return Err("ppm: P6 data truncated");
+                return Err(ImageError::TruncatedData);
             }
             if bpp == 3 {
                 for i in 0..pixel_count {
@@ -232,7 +236,7 @@ impl ImageViewer {
             }
 
             if rgb_vals.len() < pixel_count * 3 {
-                return Err("ppm: P3 data truncated");
+                return Err(ImageError::TruncatedData);
             }
 
             for i in 0..pixel_count {
@@ -251,99 +255,28 @@ impl ImageViewer {
         })
     }
 
-    /// Load a BMP image (24-bit or 32-bit uncompressed).
-    pub fn load_bmp(data: &[u8]) -> Result<Image, &'static str> {
-        if data.len() < 54 {
-            return Err("bmp: data too short for header");
-        }
-        if data[0] != 0x42 || data[1] != 0x4D {
-            return Err("bmp: invalid magic");
-        }
-
-        // BMP header fields (little-endian)
-        let data_offset = read_le_u32(data, 10) as usize;
-        let dib_size = read_le_u32(data, 14);
-        if dib_size < 40 {
-            return Err("bmp: unsupported DIB header size");
-        }
-
-        let width = read_le_i32(data, 18);
-        let height_raw = read_le_i32(data, 22);
-        let bpp = read_le_u16(data, 28) as usize;
-        let compression = read_le_u32(data, 30);
-
-        if compression != 0 && compression != 3 {
-            return Err("bmp: compressed BMPs not supported");
-        }
-        if bpp != 24 && bpp != 32 {
-            return Err("bmp: only 24-bit and 32-bit supported");
-        }
-        if width <= 0 {
-            return Err("bmp: invalid width");
-        }
-
-        let w = width as usize;
-        // Negative height means top-down storage.
-        let (h, bottom_up) = if height_raw < 0 {
-            ((-height_raw) as usize, false)
-        } else {
-            (height_raw as usize, true)
-        };
-
-        if w == 0 || h == 0 {
-            return Err("bmp: zero dimension");
-        }
-
-        let bytes_per_pixel = bpp / 8;
-        let row_size_raw = w * bytes_per_pixel;
-        // BMP rows are padded to 4-byte boundaries.
-        let row_stride = (row_size_raw + 3) & !3;
-
-        let needed = data_offset + row_stride * h;
-        if data.len() < needed {
-            return Err("bmp: pixel data truncated");
-        }
-
-        let pixel_count = w * h;
-        let mut pixels = vec![0u32; pixel_count];
-
-        for row in 0..h {
-            let src_row = if bottom_up { h - 1 - row } else { row };
-            let src_off = data_offset + src_row * row_stride;
-            let dst_off = row * w;
-
-            for col in 0..w {
-                let px_off = src_off + col * bytes_per_pixel;
-                let b = data[px_off] as u32;
-                let g = data[px_off + 1] as u32;
-                let r = data[px_off + 2] as u32;
-                let a = if bpp == 32 {
-                    data[px_off + 3] as u32
-                } else {
-                    0xFF
-                };
-                pixels[dst_off + col] = (a << 24) | (r << 16) | (g << 8) | b;
-            }
-        }
+    /// Load a PNG image.
+    pub fn load_png(data: &[u8]) -> Result<Image, ImageError> {
+        let image = image_format::decode_png(data)?;
+        Ok(codec_image_to_image(&image, ImageFormat::Png))
+    }
 
-        Ok(Image {
-            width: w,
-            height: h,
-            pixels,
-            format: ImageFormat::Bmp,
-        })
+    /// Load a BMP image (uncompressed or bit-field, 1 to 32 bpp).
+    pub fn load_bmp(data: &[u8]) -> Result<Image, ImageError> {
+        let image = image_format::decode_bmp(data)?;
+        Ok(codec_image_to_image(&image, ImageFormat::Bmp))
     }
 
     /// Load a TGA image via the video decoder, converting to BGRA u32 pixels.
-    pub fn load_tga(data: &[u8]) -> Result<Image, &'static str> {
-        let frame = crate::video::decode::decode_tga(data).map_err(|_| "tga: decode failed")?;
+    pub fn load_tga(data: &[u8]) -> Result<Image, ImageError> {
+        let frame = crate::video::decode::decode_tga(data).map_err(|_| ImageError::CorruptData)?;
         Ok(video_frame_to_image(&frame, ImageFormat::Tga))
     }
 
-    /// Load a QOI image via the video decoder, converting to BGRA u32 pixels.
-    pub fn load_qoi(data: &[u8]) -> Result<Image, &'static str> {
-        let frame = crate::video::decode::decode_qoi(data).map_err(|_| "qoi: decode failed")?;
-        Ok(video_frame_to_image(&frame, ImageFormat::Qoi))
+    /// Load a QOI image.
+    pub fn load_qoi(data: &[u8]) -> Result<Image, ImageError> {
+        let image = image_format::decode_qoi(data)?;
+        Ok(codec_image_to_image(&image, ImageFormat::Qoi))
     }
 
     /// Load an image file from raw byte data, auto-detecting the format.
@@ -353,13 +286,16 @@ impl ImageViewer {
 
         let fmt = detect_image_format(data);
         let result = match fmt {
+            ImageFormat::Png => Self::load_png(data),
             ImageFormat::Ppm => Self::load_ppm(data),
             ImageFormat::Bmp => Self::load_bmp(data),
             ImageFormat::Tga => Self::load_tga(data),
             ImageFormat::Qoi => Self::load_qoi(data),
             ImageFormat::Unknown => {
                 // Try extension-based detection
-                if filename.ends_with(".ppm") || filename.ends_with(".pnm") {
+                if filename.ends_with(".png") {
+                    Self::load_png(data)
+                } else if filename.ends_with(".ppm") || filename.ends_with(".pnm") {
                     Self::load_ppm(data)
                 } else if filename.ends_with(".bmp") {
                     Self::load_bmp(data)
@@ -368,7 +304,7 @@ impl ImageViewer {
                 } else if filename.ends_with(".qoi") {
                     Self::load_qoi(data)
                 } else {
-                    Err("unknown image format")
+                    Err(ImageError::InvalidSignature)
                 }
             }
         };
@@ -383,7 +319,7 @@ impl ImageViewer {
             }
             Err(e) => {
                 self.image = None;
-                self.state = ImageViewerState::Error(String::from(e));
+                self.state = ImageViewerState::Error(String::from(e.as_str()));
             }
         }
     }
@@ -662,21 +598,15 @@ impl ImageViewer {
 
 /// Detect image format from the first bytes of the data.
 pub fn detect_image_format(data: &[u8]) -> ImageFormat {
-    if data.len() >= 4 {
-        // QOI: magic "qoif"
-        if data[0] == b'q' && data[1] == b'o' && data[2] == b'i' && data[3] == b'f' {
-            return ImageFormat::Qoi;
-        }
+    match image_format::Format::detect(data) {
+        Some(image_format::Format::Png) => return ImageFormat::Png,
+        Some(image_format::Format::Bmp) => return ImageFormat::Bmp,
+        Some(image_format::Format::Qoi) => return ImageFormat::Qoi,
+        None => {}
     }
-    if data.len() >= 2 {
-        // PPM magic: P3 or P6
-        if data[0] == b'P' && (data[1] == b'3' || data[1] == b'6') {
-            return ImageFormat::Ppm;
-        }
-        // BMP magic: 0x42 0x4D ("BM")
-        if data[0] == 0x42 && data[1] == 0x4D {
-            return ImageFormat::Bmp;
-        }
+    // PPM magic: P3 or P6
+    if data.len() >= 2 && data[0] == b'P' && (data[1] == b'3' || data[1] == b'6') {
+        return ImageFormat::Ppm;
     }
     // TGA heuristic (no reliable magic): check header fields
     if data.len() >= 18 {
@@ -718,6 +648,16 @@ fn video_frame_to_image(frame: &crate::video::VideoFrame, fmt: ImageFormat) -> I
     }
 }
 
+/// Convert an `image-format` RGBA image into the viewer's BGRA u32 pixels.
+fn codec_image_to_image(image: &image_format::Image, fmt: ImageFormat) -> Image {
+    Image {
+        width: image.width as usize,
+        height: image.height as usize,
+        pixels: image.to_argb(),
+        format: fmt,
+    }
+}
+
 /// Scale an image to `dst_width x dst_height` using nearest-neighbor sampling.
 ///
 /// Returns a new pixel buffer.
@@ -743,37 +683,17 @@ pub fn nearest_neighbor_scale(src: &Image, dst_width: usize, dst_height: usize)
 // Integer parsing / formatting helpers (no_std friendly)
 // ---------------------------------------------------------------------------
 
-/// Parse an ASCII decimal number from a byte slice.
-fn parse_ascii_usize(bytes: &[u8]) -> Result<usize, &'static str> {
+/// Parse an ASCII decimal number from a byte slice; `None` on a non-digit
+/// or overflow.
+fn parse_ascii_usize(bytes: &[u8]) -> Option<usize> {
     let mut val: usize = 0;
     for &b in bytes {
         if !b.is_ascii_digit() {
-            return Err("non-digit in number");
+            return None;
         }
-        val = val
-            .checked_mul(10)
-            .and_then(|v| v.checked_add((b - b'0') as usize))
-            .ok_or("number overflow")?;
+        val = val.checked_mul(10)?.checked_add((b - b'0') as usize)?;
     }
-    Ok(val)
-}
-
-/// Read a little-endian u32 from a byte slice at the given offset.
-fn read_le_u32(data: &[u8], off: usize) -> u32 {
-    (data[off] as u32)
-        | ((data[off + 1] as u32) << 8)
-        | ((data[off + 2] as u32) << 16)
-        | ((data[off + 3] as u32) << 24)
-}
-
-/// Read a little-endian i32 from a byte slice at the given offset.
-fn read_le_i32(data: &[u8], off: usize) -> i32 {
-    read_le_u32(data, off) as i32
-}
-
-/// Read a little-endian u16 from a byte slice at the given offset.
-fn read_le_u16(data: &[u8], off: usize) -> u16 {
-    (data[off] as u16) | ((data[off + 1] as u16) << 8)
+    Some(val)
 }
 
 impl ImageViewer {
@@ -6,9 +6,12 @@
 pub mod a11y;
 pub mod animation;
 pub mod app_switcher;
+pub mod client_window;
+pub mod config;
 pub mod desktop_ext;
 pub mod desktop_icons;
 pub mod display_manager;
+pub mod editor_plugin;
 pub mod file_assoc;
 pub mod file_manager;
 pub mod font;
@@ -22,11 +25,13 @@ pub mod panel;
 pub mod pdf;
 pub mod renderer;
 pub mod screen_lock;
+pub mod screenshot;
 pub mod session_config;
 pub mod settings;
 pub mod syntax;
 pub mod systray;
 pub mod terminal;
+pub mod text_buffer;
 pub mod text_editor;
 pub mod wayland;
 pub mod window_manager;
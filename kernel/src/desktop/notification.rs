@@ -2,15 +2,23 @@
 //!
 //! Provides toast-style notification popups displayed as overlay surfaces.
 //! Notifications stack from the top-right corner of the screen and auto-expire
-//! based on urgency level. Supports programmatic dismiss and tick-based expiry.
+//! based on urgency level or a per-notification timeout. Supports programmatic
+//! dismiss and tick-based expiry.
+//!
+//! A toast can carry an icon (an image path or an icon name) and up to
+//! [`MAX_ACTIONS`] action buttons. Clicking a button invokes that action,
+//! clicking the body invokes the [`DEFAULT_ACTION`] if there is one, and the
+//! close box dismisses the toast. What happened to each notification is kept
+//! for [`NotificationManager::status`] and, if the sender supplied a reply
+//! endpoint, pushed to it by `services::notification_ipc`.
 
 #![allow(dead_code)]
 
-use alloc::{string::String, vec::Vec};
+use alloc::{collections::VecDeque, string::String, vec::Vec};
 
 use spin::Mutex;
 
-use crate::sync::once_lock::GlobalState;
+use crate::{ipc::EndpointId, sync::once_lock::GlobalState};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -47,10 +55,34 @@ const BODY_COLOR: u32 = 0xFFAAAAAA;
 /// App name text color (dim).
 const APP_NAME_COLOR: u32 = 0xFF777777;
 
+/// Action button background color.
+const BUTTON_BG_COLOR: u32 = 0xFF404040;
+
 /// Font dimensions (8x16 bitmap font).
 const CHAR_W: usize = 8;
 const CHAR_H: usize = 16;
 
+/// Edge length of the toast icon in pixels.
+const ICON_SIZE: usize = 32;
+
+/// Height of the action button row below the text.
+const ACTION_ROW_HEIGHT: usize = 24;
+
+/// Gap between action buttons.
+const BUTTON_GAP: usize = 6;
+
+/// Width of the close box in the top-right corner of a toast.
+const CLOSE_BOX_WIDTH: usize = 16;
+
+/// Most action buttons shown on one toast.
+pub const MAX_ACTIONS: usize = 3;
+
+/// Key of the action invoked by clicking the toast body; it has no button.
+pub const DEFAULT_ACTION: &str = "default";
+
+/// How many finished notifications [`NotificationManager::status`] remembers.
+const MAX_OUTCOMES: usize = 32;
+
 // ---------------------------------------------------------------------------
 // Types
 // ---------------------------------------------------------------------------
@@ -95,6 +127,106 @@ impl NotificationUrgency {
     }
 }
 
+/// A button on a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationAction {
+    /// Identifier reported back to the sender.
+    pub key: String,
+    /// Button label.
+    pub label: String,
+}
+
+/// Why a notification left the screen (numbered as in the freedesktop
+/// notification spec).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CloseReason {
+    /// Its timeout ran out.
+    Expired = 1,
+    /// The user clicked it away.
+    Dismissed = 2,
+    /// The sender or the system closed it.
+    Closed = 3,
+}
+
+/// Something the sender of a notification is told about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationEvent {
+    /// The user invoked action `action` (an index into the notification's
+    /// actions).
+    ActionInvoked { id: u32, action: usize },
+    /// The notification was removed.
+    Closed { id: u32, reason: CloseReason },
+}
+
+impl NotificationEvent {
+    /// The notification this event is about.
+    pub fn id(self) -> u32 {
+        match self {
+            Self::ActionInvoked { id, .. } | Self::Closed { id, .. } => id,
+        }
+    }
+}
+
+/// State of a notification as reported by [`NotificationManager::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationStatus {
+    /// Still on screen.
+    Active,
+    /// Closed after the user invoked this action.
+    ActionInvoked(usize),
+    /// Closed without an action.
+    Closed(CloseReason),
+}
+
+/// Everything needed to post a notification.
+#[derive(Debug, Clone)]
+pub struct NotificationRequest {
+    /// Short summary / title line.
+    pub summary: String,
+    /// Longer body text (may be empty).
+    pub body: String,
+    /// Urgency level.
+    pub urgency: NotificationUrgency,
+    /// Name of the sending application.
+    pub app_name: String,
+    /// Image path (starting with `/`) or icon name.
+    pub icon: Option<String>,
+    /// Display time in milliseconds; `None` uses the urgency default and
+    /// `Some(0)` keeps the toast until it is dismissed.
+    pub timeout_ms: Option<u32>,
+    /// Action buttons; only the first [`MAX_ACTIONS`] are kept.
+    pub actions: Vec<NotificationAction>,
+    /// Endpoint that receives [`NotificationEvent`]s for this notification.
+    pub reply_endpoint: Option<EndpointId>,
+}
+
+impl NotificationRequest {
+    /// A request with no icon, actions or reply endpoint and the default
+    /// timeout.
+    pub fn new(summary: &str, body: &str, urgency: NotificationUrgency, app_name: &str) -> Self {
+        Self {
+            summary: String::from(summary),
+            body: String::from(body),
+            urgency,
+            app_name: String::from(app_name),
+            icon: None,
+            timeout_ms: None,
+            actions: Vec::new(),
+            reply_endpoint: None,
+        }
+    }
+}
+
+/// Part of a toast under the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToastHit {
+    Close,
+    /// Index into the notification's actions.
+    Action(usize),
+    Body,
+}
+
 /// A single desktop notification.
 #[derive(Debug, Clone)]
 pub struct Notification {
@@ -108,6 +240,14 @@ pub struct Notification {
     pub urgency: NotificationUrgency,
     /// Name of the application that sent this notification.
     pub app_name: String,
+    /// Image path or icon name.
+    pub icon: Option<String>,
+    /// Icon decoded from an image path, `ICON_SIZE` squared BGRA pixels.
+    icon_pixels: Option<Vec<u32>>,
+    /// Action buttons.
+    pub actions: Vec<NotificationAction>,
+    /// Endpoint told about actions and closing.
+    pub reply_endpoint: Option<EndpointId>,
     /// Tick count when the notification was created.
     pub created_tick: u64,
     /// Number of ticks after creation when the notification expires.
@@ -121,6 +261,19 @@ impl Notification {
     pub fn is_expired(&self, current_tick: u64) -> bool {
         current_tick >= self.created_tick.saturating_add(self.expire_ticks)
     }
+
+    /// Actions shown as buttons, with their indices into `actions`.
+    fn buttons(&self) -> impl Iterator<Item = (usize, &NotificationAction)> {
+        self.actions
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.key != DEFAULT_ACTION)
+    }
+
+    /// Index of the action invoked by clicking the body.
+    fn default_action(&self) -> Option<usize> {
+        self.actions.iter().position(|a| a.key == DEFAULT_ACTION)
+    }
 }
 
 /// Manages the set of active desktop notifications.
@@ -141,6 +294,10 @@ pub struct NotificationManager {
     position_x: usize,
     /// Y position (top edge) for the first toast.
     position_y: usize,
+    /// Recent events of finished notifications, oldest first.
+    outcomes: VecDeque<NotificationEvent>,
+    /// Events waiting to be sent to reply endpoints.
+    pending_events: Vec<(EndpointId, NotificationEvent)>,
 }
 
 impl NotificationManager {
@@ -158,6 +315,8 @@ impl NotificationManager {
             toast_margin,
             position_x: screen_width.saturating_sub(toast_width + toast_margin),
             position_y: toast_margin,
+            outcomes: VecDeque::new(),
+            pending_events: Vec::new(),
         }
     }
 
@@ -169,18 +328,50 @@ impl NotificationManager {
         urgency: NotificationUrgency,
         app_name: String,
     ) -> u32 {
+        self.post(NotificationRequest {
+            summary,
+            body,
+            urgency,
+            app_name,
+            icon: None,
+            timeout_ms: None,
+            actions: Vec::new(),
+            reply_endpoint: None,
+        })
+    }
+
+    /// Post a notification with icon, timeout and actions. Returns the
+    /// assigned notification ID.
+    pub fn post(&mut self, request: NotificationRequest) -> u32 {
+        self.post_at(request, read_tick())
+    }
+
+    fn post_at(&mut self, mut request: NotificationRequest, current_tick: u64) -> u32 {
         let id = self.next_id;
         self.next_id = self.next_id.wrapping_add(1);
 
-        let current_tick = read_tick();
-        let expire_ticks = urgency.default_expire_ticks();
+        let expire_ticks = match request.timeout_ms {
+            None => request.urgency.default_expire_ticks(),
+            Some(0) => u64::MAX,
+            Some(ms) => ms as u64,
+        };
+        request.actions.truncate(MAX_ACTIONS);
+        let icon_pixels = request
+            .icon
+            .as_deref()
+            .filter(|icon| icon.starts_with('/'))
+            .and_then(load_icon);
 
         self.notifications.push(Notification {
             id,
-            summary,
-            body,
-            urgency,
-            app_name,
+            summary: request.summary,
+            body: request.body,
+            urgency: request.urgency,
+            app_name: request.app_name,
+            icon: request.icon,
+            icon_pixels,
+            actions: request.actions,
+            reply_endpoint: request.reply_endpoint,
             created_tick: current_tick,
             expire_ticks,
             dismissed: false,
@@ -191,27 +382,191 @@ impl NotificationManager {
 
     /// Dismiss a specific notification by ID.
     pub fn dismiss(&mut self, id: u32) {
-        for n in &mut self.notifications {
-            if n.id == id {
-                n.dismissed = true;
-                return;
-            }
-        }
+        self.close(id, CloseReason::Closed);
     }
 
     /// Dismiss all active notifications.
     pub fn dismiss_all(&mut self) {
-        for n in &mut self.notifications {
-            n.dismissed = true;
+        let ids: Vec<u32> = self
+            .notifications
+            .iter()
+            .filter(|n| !n.dismissed)
+            .map(|n| n.id)
+            .collect();
+        for id in ids {
+            self.close(id, CloseReason::Closed);
         }
     }
 
     /// Tick the notification manager: remove expired and dismissed entries.
     pub fn tick(&mut self, current_tick: u64) {
+        let expired: Vec<(u32, Option<EndpointId>)> = self
+            .notifications
+            .iter()
+            .filter(|n| !n.dismissed && n.is_expired(current_tick))
+            .map(|n| (n.id, n.reply_endpoint))
+            .collect();
+        for (id, endpoint) in expired {
+            self.record(
+                endpoint,
+                NotificationEvent::Closed {
+                    id,
+                    reason: CloseReason::Expired,
+                },
+            );
+        }
         self.notifications
             .retain(|n| !n.dismissed && !n.is_expired(current_tick));
     }
 
+    /// Mark a notification closed and record why.
+    fn close(&mut self, id: u32, reason: CloseReason) {
+        let Some(n) = self
+            .notifications
+            .iter_mut()
+            .find(|n| n.id == id && !n.dismissed)
+        else {
+            return;
+        };
+        n.dismissed = true;
+        let endpoint = n.reply_endpoint;
+        self.record(endpoint, NotificationEvent::Closed { id, reason });
+    }
+
+    /// Remember an event for `status` and queue it for the sender.
+    fn record(&mut self, endpoint: Option<EndpointId>, event: NotificationEvent) {
+        if let Some(endpoint) = endpoint {
+            self.pending_events.push((endpoint, event));
+        }
+        if self.outcomes.len() >= MAX_OUTCOMES {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(event);
+    }
+
+    /// Take the events waiting to be sent to reply endpoints.
+    pub fn take_events(&mut self) -> Vec<(EndpointId, NotificationEvent)> {
+        core::mem::take(&mut self.pending_events)
+    }
+
+    /// What became of notification `id`, if it is on screen or finished
+    /// recently.
+    pub fn status(&self, id: u32) -> Option<NotificationStatus> {
+        self.status_at(id, read_tick())
+    }
+
+    fn status_at(&self, id: u32, current_tick: u64) -> Option<NotificationStatus> {
+        if let Some(n) = self.notifications.iter().find(|n| n.id == id) {
+            if !n.dismissed {
+                return Some(if n.is_expired(current_tick) {
+                    NotificationStatus::Closed(CloseReason::Expired)
+                } else {
+                    NotificationStatus::Active
+                });
+            }
+        }
+        let mut status = None;
+        for event in self.outcomes.iter().filter(|e| e.id() == id) {
+            match *event {
+                NotificationEvent::ActionInvoked { action, .. } => {
+                    return Some(NotificationStatus::ActionInvoked(action))
+                }
+                NotificationEvent::Closed { reason, .. } => {
+                    status = Some(NotificationStatus::Closed(reason))
+                }
+            }
+        }
+        status
+    }
+
+    /// Handle a left click at screen position (`x`, `y`). Returns `true` if
+    /// it landed on a toast.
+    pub fn handle_click(&mut self, x: usize, y: usize, current_tick: u64) -> bool {
+        let Some((id, hit)) = self.hit_test(x, y, current_tick) else {
+            return false;
+        };
+        let Some(n) = self.notifications.iter().find(|n| n.id == id) else {
+            return false;
+        };
+        let endpoint = n.reply_endpoint;
+        let action = match hit {
+            ToastHit::Close => None,
+            ToastHit::Action(action) => Some(action),
+            ToastHit::Body => n.default_action(),
+        };
+        if let Some(action) = action {
+            self.record(endpoint, NotificationEvent::ActionInvoked { id, action });
+        }
+        self.close(id, CloseReason::Dismissed);
+        true
+    }
+
+    /// Height of the toast for `n`.
+    fn height_of(&self, n: &Notification) -> usize {
+        if n.buttons().next().is_some() {
+            self.toast_height + ACTION_ROW_HEIGHT
+        } else {
+            self.toast_height
+        }
+    }
+
+    /// Visible notifications with the top edge of each toast.
+    fn layout_at(&self, current_tick: u64) -> Vec<(usize, &Notification)> {
+        let mut y = self.position_y;
+        self.visible_notifications_at(current_tick)
+            .into_iter()
+            .map(|n| {
+                let top = y;
+                y += self.height_of(n) + self.toast_margin;
+                (top, n)
+            })
+            .collect()
+    }
+
+    /// Rectangle (x, y, width, height) of button `slot` out of `count`, on a
+    /// toast whose top-left corner is (`tx`, `ty`).
+    fn button_rect(
+        &self,
+        tx: usize,
+        ty: usize,
+        slot: usize,
+        count: usize,
+    ) -> (usize, usize, usize, usize) {
+        let inner = self.toast_width - 16;
+        let w = (inner - BUTTON_GAP * (count - 1)) / count;
+        (
+            tx + 8 + slot * (w + BUTTON_GAP),
+            ty + self.toast_height,
+            w,
+            ACTION_ROW_HEIGHT - 4,
+        )
+    }
+
+    /// Find the toast and the part of it at (`x`, `y`).
+    fn hit_test(&self, x: usize, y: usize, current_tick: u64) -> Option<(u32, ToastHit)> {
+        let tx = self.position_x;
+        if x < tx || x >= tx + self.toast_width {
+            return None;
+        }
+        for (ty, n) in self.layout_at(current_tick) {
+            if y < ty || y >= ty + self.height_of(n) {
+                continue;
+            }
+            if x >= tx + self.toast_width - CLOSE_BOX_WIDTH && y < ty + CHAR_H + 4 {
+                return Some((n.id, ToastHit::Close));
+            }
+            let count = n.buttons().count();
+            for (slot, (index, _)) in n.buttons().enumerate() {
+                let (bx, by, bw, bh) = self.button_rect(tx, ty, slot, count);
+                if x >= bx && x < bx + bw && y >= by && y < by + bh {
+                    return Some((n.id, ToastHit::Action(index)));
+                }
+            }
+            return Some((n.id, ToastHit::Body));
+        }
+        None
+    }
+
     /// Return references to the currently visible (non-dismissed, non-expired)
     /// notifications, up to `max_visible`.
     pub fn visible_notifications(&self) -> Vec<&Notification> {
@@ -245,17 +600,11 @@ impl NotificationManager {
         buf_height: usize,
         current_tick: u64,
     ) {
-        let visible = self.visible_notifications_at(current_tick);
-        if visible.is_empty() {
-            return;
-        }
-
         let tw = self.toast_width;
-        let th = self.toast_height;
 
-        for (idx, notif) in visible.iter().enumerate() {
+        for (ty, notif) in self.layout_at(current_tick) {
             let tx = self.position_x;
-            let ty = self.position_y + idx * (th + self.toast_margin);
+            let th = self.height_of(notif);
 
             // Skip if toast would be off-screen
             if ty + th > buf_height || tx + tw > buf_width {
@@ -263,71 +612,128 @@ impl NotificationManager {
             }
 
             let border_color = notif.urgency.border_color();
+            fill_rect_u32(
+                buffer,
+                buf_width,
+                tx,
+                ty,
+                tw,
+                th,
+                TOAST_BG_COLOR,
+                border_color,
+            );
 
-            // Draw toast rectangle (background + 1px border)
-            for row in 0..th {
-                for col in 0..tw {
-                    let px = tx + col;
-                    let py = ty + row;
-                    let offset = py * buf_width + px;
-                    if offset >= buffer.len() {
-                        continue;
-                    }
+            // Render app name (top-left, small and dim) and the close box
+            let app_max = (tw - 16 - CLOSE_BOX_WIDTH) / CHAR_W;
+            render_text_u32(
+                buffer,
+                buf_width,
+                tx + 8,
+                ty + 4,
+                &notif.app_name,
+                app_max,
+                APP_NAME_COLOR,
+            );
+            render_glyph_u32(
+                buffer,
+                buf_width,
+                tx + tw - CLOSE_BOX_WIDTH,
+                ty + 4,
+                b'x',
+                SUMMARY_COLOR,
+            );
 
-                    // 1-pixel border
-                    if row == 0 || row == th - 1 || col == 0 || col == tw - 1 {
-                        buffer[offset] = border_color;
-                    } else {
-                        buffer[offset] = TOAST_BG_COLOR;
+            // Icon to the left of summary and body
+            let mut text_x = tx + 8;
+            let icon_y = ty + 4 + CHAR_H + 2;
+            if let Some(ref pixels) = notif.icon_pixels {
+                for (row, line) in pixels.chunks_exact(ICON_SIZE).enumerate() {
+                    let start = (icon_y + row) * buf_width + text_x;
+                    if let Some(dst) = buffer.get_mut(start..start + ICON_SIZE) {
+                        for (d, &p) in dst.iter_mut().zip(line) {
+                            if p >> 24 >= 0x80 {
+                                *d = p;
+                            }
+                        }
                     }
                 }
-            }
-
-            // Render app name (top-left, small and dim)
-            let app_bytes = notif.app_name.as_bytes();
-            let app_max = (tw - 16) / CHAR_W;
-            let app_y = ty + 4;
-            for (i, &ch) in app_bytes.iter().take(app_max).enumerate() {
+                text_x += ICON_SIZE + 8;
+            } else if let Some(ref icon) = notif.icon {
+                // Named icon without artwork: a badge with its initial
+                fill_rect_u32(
+                    buffer,
+                    buf_width,
+                    text_x,
+                    icon_y,
+                    ICON_SIZE,
+                    ICON_SIZE,
+                    BUTTON_BG_COLOR,
+                    border_color,
+                );
+                let initial = icon.bytes().next().unwrap_or(b'?').to_ascii_uppercase();
                 render_glyph_u32(
                     buffer,
                     buf_width,
-                    tx + 8 + i * CHAR_W,
-                    app_y,
-                    ch,
-                    APP_NAME_COLOR,
+                    text_x + (ICON_SIZE - CHAR_W) / 2,
+                    icon_y + (ICON_SIZE - CHAR_H) / 2,
+                    initial,
+                    SUMMARY_COLOR,
                 );
+                text_x += ICON_SIZE + 8;
             }
+            let text_max = (tx + tw - 8 - text_x) / CHAR_W;
 
             // Render summary (bold-ish white, below app name)
-            let sum_bytes = notif.summary.as_bytes();
-            let sum_max = (tw - 16) / CHAR_W;
             let sum_y = ty + 4 + CHAR_H + 2;
-            for (i, &ch) in sum_bytes.iter().take(sum_max).enumerate() {
-                render_glyph_u32(
+            render_text_u32(
+                buffer,
+                buf_width,
+                text_x,
+                sum_y,
+                &notif.summary,
+                text_max,
+                SUMMARY_COLOR,
+            );
+
+            // Render body (gray, below summary, may truncate)
+            let body_y = ty + 4 + (CHAR_H + 2) * 2;
+            if body_y + CHAR_H <= ty + self.toast_height {
+                render_text_u32(
                     buffer,
                     buf_width,
-                    tx + 8 + i * CHAR_W,
-                    sum_y,
-                    ch,
-                    SUMMARY_COLOR,
+                    text_x,
+                    body_y,
+                    &notif.body,
+                    text_max,
+                    BODY_COLOR,
                 );
             }
 
-            // Render body (gray, below summary, may truncate)
-            let body_bytes = notif.body.as_bytes();
-            let body_max = (tw - 16) / CHAR_W;
-            let body_y = ty + 4 + (CHAR_H + 2) * 2;
-            if body_y + CHAR_H <= ty + th {
-                for (i, &ch) in body_bytes.iter().take(body_max).enumerate() {
-                    render_glyph_u32(
-                        buffer,
-                        buf_width,
-                        tx + 8 + i * CHAR_W,
-                        body_y,
-                        ch,
-                        BODY_COLOR,
-                    );
-                }
+            // Action buttons along the bottom
+            let count = notif.buttons().count();
+            for (slot, (_, action)) in notif.buttons().enumerate() {
+                let (bx, by, bw, bh) = self.button_rect(tx, ty, slot, count);
+                fill_rect_u32(
+                    buffer,
+                    buf_width,
+                    bx,
+                    by,
+                    bw,
+                    bh,
+                    BUTTON_BG_COLOR,
+                    border_color,
+                );
+                let max = (bw - 4) / CHAR_W;
+                let len = action.label.len().min(max);
+                render_text_u32(
+                    buffer,
+                    buf_width,
+                    bx + (bw - len * CHAR_W) / 2,
+                    by + (bh - CHAR_H) / 2,
+                    &action.label,
+                    max,
+                    SUMMARY_COLOR,
+                );
             }
         }
     }
@@ -344,9 +750,51 @@ impl NotificationManager {
 }
 
 // ---------------------------------------------------------------------------
-// Glyph rendering helper (u32 pixel buffer)
+// Drawing helpers (u32 pixel buffer)
 // ---------------------------------------------------------------------------
 
+/// Fill a rectangle with a 1-pixel border.
+#[allow(clippy::too_many_arguments)]
+fn fill_rect_u32(
+    buf: &mut [u32],
+    buf_width: usize,
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    fill: u32,
+    border: u32,
+) {
+    for row in 0..h {
+        let start = (y + row) * buf_width + x;
+        let Some(line) = buf.get_mut(start..start + w) else {
+            return;
+        };
+        if row == 0 || row == h - 1 {
+            line.fill(border);
+        } else {
+            line.fill(fill);
+            line[0] = border;
+            line[w - 1] = border;
+        }
+    }
+}
+
+/// Render up to `max_chars` characters of `text` starting at (`px`, `py`).
+fn render_text_u32(
+    buf: &mut [u32],
+    buf_width: usize,
+    px: usize,
+    py: usize,
+    text: &str,
+    max_chars: usize,
+    color: u32,
+) {
+    for (i, &ch) in text.as_bytes().iter().take(max_chars).enumerate() {
+        render_glyph_u32(buf, buf_width, px + i * CHAR_W, py, ch, color);
+    }
+}
+
 /// Render a single 8x16 glyph into a u32 (BGRA packed) pixel buffer.
 ///
 /// Only foreground pixels are written; background pixels are left untouched
@@ -369,6 +817,23 @@ fn render_glyph_u32(buf: &mut [u32], buf_width: usize, px: usize, py: usize, ch:
     }
 }
 
+/// Decode the image at `path` and scale it to an `ICON_SIZE` square of BGRA
+/// pixels. Returns `None` if the file is missing or not an image.
+fn load_icon(path: &str) -> Option<Vec<u32>> {
+    crate::fs::try_get_vfs()?;
+    let data = crate::fs::read_file(path).ok()?;
+    let image = image_format::decode(&data).ok()?;
+    let (iw, ih) = (image.width as usize, image.height as usize);
+    let argb = image.to_argb();
+    let mut pixels = Vec::with_capacity(ICON_SIZE * ICON_SIZE);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            pixels.push(argb[(y * ih / ICON_SIZE) * iw + x * iw / ICON_SIZE]);
+        }
+    }
+    Some(pixels)
+}
+
 // ---------------------------------------------------------------------------
 // Tick source helper
 // ---------------------------------------------------------------------------
@@ -418,18 +883,200 @@ pub fn notify(summary: &str, body: &str, urgency: NotificationUrgency, app_name:
     .unwrap_or(0)
 }
 
+/// Convenience: post a notification with icon, timeout and actions.
+/// Returns `None` before the desktop is up.
+pub fn post(request: NotificationRequest) -> Option<u32> {
+    with_notification_manager(|mgr| mgr.post(request))
+}
+
 /// Convenience: dismiss a notification by ID.
 pub fn dismiss(id: u32) {
     with_notification_manager(|mgr| mgr.dismiss(id));
+    send_events();
 }
 
 /// Convenience: dismiss all notifications.
 pub fn dismiss_all() {
     with_notification_manager(|mgr| mgr.dismiss_all());
+    send_events();
 }
 
 /// Convenience: tick the notification manager (call from render loop).
 pub fn tick() {
     let current = read_tick();
     with_notification_manager(|mgr| mgr.tick(current));
+    send_events();
+}
+
+/// Convenience: route a left click at (`x`, `y`) to the toasts. Returns
+/// `true` if a toast took it.
+pub fn handle_click(x: i32, y: i32) -> bool {
+    let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+        return false;
+    };
+    let current = read_tick();
+    let hit = with_notification_manager(|mgr| mgr.handle_click(x, y, current)).unwrap_or(false);
+    send_events();
+    hit
+}
+
+/// Convenience: draw the toasts over a composited frame.
+pub fn render_overlay(buffer: &mut [u32], buf_width: usize, buf_height: usize) {
+    let current = read_tick();
+    with_notification_manager(|mgr| {
+        if mgr.active_count() > 0 {
+            mgr.render_to_buffer(buffer, buf_width, buf_height, current);
+        }
+    });
+}
+
+/// Deliver queued events to reply endpoints. Runs after the manager lock is
+/// released so a receiver posting a notification cannot deadlock.
+fn send_events() {
+    let events = with_notification_manager(|mgr| mgr.take_events()).unwrap_or_default();
+    for (endpoint, event) in events {
+        crate::services::notification_ipc::send_event(endpoint, event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn action(key: &str, label: &str) -> NotificationAction {
+        NotificationAction {
+            key: String::from(key),
+            label: String::from(label),
+        }
+    }
+
+    fn request(summary: &str) -> NotificationRequest {
+        NotificationRequest::new(summary, "", NotificationUrgency::Normal, "test")
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let mut mgr = NotificationManager::new(1024, 768);
+        let default = mgr.post_at(request("default"), 0);
+        let short = mgr.post_at(
+            NotificationRequest {
+                timeout_ms: Some(100),
+                ..request("short")
+            },
+            0,
+        );
+        let sticky = mgr.post_at(
+            NotificationRequest {
+                timeout_ms: Some(0),
+                ..request("sticky")
+            },
+            0,
+        );
+
+        mgr.tick(200);
+        assert_eq!(
+            mgr.status_at(short, 200),
+            Some(NotificationStatus::Closed(CloseReason::Expired))
+        );
+        assert_eq!(
+            mgr.status_at(default, 200),
+            Some(NotificationStatus::Active)
+        );
+
+        mgr.tick(1_000_000);
+        assert_eq!(
+            mgr.status_at(default, 1_000_000),
+            Some(NotificationStatus::Closed(CloseReason::Expired))
+        );
+        assert_eq!(
+            mgr.status_at(sticky, 1_000_000),
+            Some(NotificationStatus::Active)
+        );
+        assert_eq!(mgr.status_at(99, 0), None);
+    }
+
+    #[test]
+    fn test_click_invokes_actions() {
+        let mut mgr = NotificationManager::new(1024, 768);
+        let id = mgr.post_at(
+            NotificationRequest {
+                actions: vec![action("yes", "Yes"), action("no", "No")],
+                reply_endpoint: Some(77),
+                ..request("question")
+            },
+            0,
+        );
+        let (ty, _) = mgr.layout_at(0)[0];
+        let (bx, by, _, _) = mgr.button_rect(mgr.position_x, ty, 1, 2);
+
+        // Clicks outside the toast column are not taken
+        assert!(!mgr.handle_click(0, by, 0));
+
+        assert!(mgr.handle_click(bx + 1, by + 1, 0));
+        assert_eq!(
+            mgr.status_at(id, 0),
+            Some(NotificationStatus::ActionInvoked(1))
+        );
+        assert_eq!(
+            mgr.take_events(),
+            vec![
+                (77, NotificationEvent::ActionInvoked { id, action: 1 }),
+                (
+                    77,
+                    NotificationEvent::Closed {
+                        id,
+                        reason: CloseReason::Dismissed
+                    }
+                ),
+            ]
+        );
+        assert!(mgr.take_events().is_empty());
+    }
+
+    #[test]
+    fn test_click_body_and_close_box() {
+        let mut mgr = NotificationManager::new(1024, 768);
+        let first = mgr.post_at(
+            NotificationRequest {
+                actions: vec![action(DEFAULT_ACTION, "")],
+                ..request("first")
+            },
+            0,
+        );
+        let second = mgr.post_at(request("second"), 0);
+        let layout: Vec<usize> = mgr.layout_at(0).iter().map(|&(y, _)| y).collect();
+        let x = mgr.position_x;
+
+        // The default action has no button, so the first toast keeps the
+        // base height.
+        assert_eq!(layout[1], layout[0] + mgr.toast_height + mgr.toast_margin);
+
+        assert!(mgr.handle_click(x + 20, layout[0] + 40, 0));
+        assert_eq!(
+            mgr.status_at(first, 0),
+            Some(NotificationStatus::ActionInvoked(0))
+        );
+
+        // The second toast has moved up into the freed slot.
+        let (y, _) = mgr.layout_at(0)[0];
+        assert_eq!(y, layout[0]);
+        assert!(mgr.handle_click(x + mgr.toast_width - 4, y + 4, 0));
+        assert_eq!(
+            mgr.status_at(second, 0),
+            Some(NotificationStatus::Closed(CloseReason::Dismissed))
+        );
+        // Nothing was registered for replies.
+        assert!(mgr.take_events().is_empty());
+
+        let third = mgr.post_at(request("third"), 0);
+        mgr.dismiss_all();
+        assert_eq!(
+            mgr.status_at(third, 0),
+            Some(NotificationStatus::Closed(CloseReason::Closed))
+        );
+        mgr.tick(0);
+        assert!(mgr.notifications.is_empty());
+    }
 }
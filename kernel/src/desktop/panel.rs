@@ -451,28 +451,26 @@ impl Panel {
         let w = self.screen_width as usize;
         let h = PANEL_HEIGHT as usize;
         let stride = w * 4;
+        let colors = crate::desktop::config::colors();
+        let text = colors.panel_foreground;
 
-        // Dark background (0x2D2D2D)
+        // Theme panel background
+        let background = colors.panel_background.to_bgra();
         for y in 0..h {
             for x in 0..w {
                 let offset = y * stride + x * 4;
                 if offset + 3 < buf.len() {
-                    buf[offset] = 0x2D; // B
-                    buf[offset + 1] = 0x2D; // G
-                    buf[offset + 2] = 0x2D; // R
-                    buf[offset + 3] = 0xFF; // A
+                    buf[offset..offset + 4].copy_from_slice(&background);
                 }
             }
         }
 
-        // Top border line (subtle highlight)
+        // Top border line
+        let border = colors.window_border.to_bgra();
         for x in 0..w {
             let offset = x * 4;
             if offset + 3 < buf.len() {
-                buf[offset] = 0x44; // B
-                buf[offset + 1] = 0x44; // G
-                buf[offset + 2] = 0x44; // R
-                buf[offset + 3] = 0xFF;
+                buf[offset..offset + 4].copy_from_slice(&border);
             }
         }
 
@@ -484,10 +482,7 @@ impl Panel {
         for y in 4..h - 4 {
             let offset = y * stride + sep_x * 4;
             if offset + 3 < buf.len() {
-                buf[offset] = 0x55; // B
-                buf[offset + 1] = 0x55; // G
-                buf[offset + 2] = 0x55; // R
-                buf[offset + 3] = 0xFF;
+                buf[offset..offset + 4].copy_from_slice(&border);
             }
         }
 
@@ -498,33 +493,28 @@ impl Panel {
             let btn_w = button.width as usize;
 
             // Button background (lighter if focused)
-            let (br, bg, bb) = if button.focused {
-                (0x50, 0x50, 0x70)
+            let button_bg = if button.focused {
+                colors.button_hover.to_bgra()
             } else {
-                (0x40, 0x40, 0x40)
+                colors.button_background.to_bgra()
             };
 
             for y in 4..h - 4 {
                 for x in btn_x..(btn_x + btn_w).min(w) {
                     let offset = y * stride + x * 4;
                     if offset + 3 < buf.len() {
-                        buf[offset] = bb;
-                        buf[offset + 1] = bg;
-                        buf[offset + 2] = br;
-                        buf[offset + 3] = 0xFF;
+                        buf[offset..offset + 4].copy_from_slice(&button_bg);
                     }
                 }
             }
 
             // Focused button underline indicator
             if button.focused {
+                let accent = colors.accent.to_bgra();
                 for x in btn_x..(btn_x + btn_w).min(w) {
                     let offset = (h - 3) * stride + x * 4;
                     if offset + 3 < buf.len() {
-                        buf[offset] = 0xDD; // B (accent blue)
-                        buf[offset + 1] = 0x88; // G
-                        buf[offset + 2] = 0x44; // R
-                        buf[offset + 3] = 0xFF;
+                        buf[offset..offset + 4].copy_from_slice(&accent);
                     }
                 }
             }
@@ -533,7 +523,14 @@ impl Panel {
             let title_bytes = button.title.as_bytes();
             let max_chars = (btn_w / 8).min(14);
             for (i, &ch) in title_bytes.iter().take(max_chars).enumerate() {
-                render_char_to_buf(buf, stride, btn_x + 4 + i * 8, 10, ch, (0xCC, 0xCC, 0xCC));
+                render_char_to_buf(
+                    buf,
+                    stride,
+                    btn_x + 4 + i * 8,
+                    10,
+                    ch,
+                    (text.red(), text.green(), text.blue()),
+                );
             }
         }
 
@@ -541,7 +538,14 @@ impl Panel {
         let clock = self.clock_text.read();
         let clock_x = w.saturating_sub(clock.len() * 8 + 12);
         for (i, &ch) in clock.as_bytes().iter().enumerate() {
-            render_char_to_buf(buf, stride, clock_x + i * 8, 10, ch, (0xBB, 0xBB, 0xBB));
+            render_char_to_buf(
+                buf,
+                stride,
+                clock_x + i * 8,
+                10,
+                ch,
+                (text.red(), text.green(), text.blue()),
+            );
         }
     }
 
@@ -579,13 +583,11 @@ impl Panel {
 
             // Active workspace underline
             if i == ws.active {
+                let accent = crate::desktop::config::colors().accent.to_bgra();
                 for x in btn_x..(btn_x + btn_w).min(max_w) {
                     let offset = (h - 4) * stride + x * 4;
                     if offset + 3 < buf.len() {
-                        buf[offset] = 0xDD; // B (accent blue)
-                        buf[offset + 1] = 0x88; // G
-                        buf[offset + 2] = 0x44; // R
-                        buf[offset + 3] = 0xFF;
+                        buf[offset..offset + 4].copy_from_slice(&accent);
                     }
                 }
             }
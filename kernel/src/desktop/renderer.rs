@@ -1,8 +1,8 @@
 //! Desktop Renderer
 //!
 //! Connects the Wayland compositor's back-buffer to the hardware framebuffer.
-//! Creates the initial desktop scene (background gradient, panel, terminal
-//! placeholder) and runs the compositing loop.
+//! Creates the initial desktop scene (wallpaper or background gradient,
+//! panel, terminal placeholder) and runs the compositing loop.
 //!
 //! Includes server-side decoration (SSD) types and rendering functions that
 //! define the complete decoration API surface. Some items (hit-testing, button
@@ -21,6 +21,10 @@ use crate::graphics::{
 static NEXT_SURFACE_ID: AtomicU32 = AtomicU32::new(2000);
 static NEXT_POOL_ID: AtomicU32 = AtomicU32::new(200);
 
+/// Compositor surface and SHM pool of the desktop background.
+const BG_SURFACE_ID: u32 = 1000;
+const BG_POOL_ID: u32 = 100;
+
 /// Initialize the desktop environment and start the compositor.
 ///
 /// This is the main entry point called by the `startgui` shell command.
@@ -91,9 +95,15 @@ pub fn start_desktop() {
     // triggering the GUI exit guard.
     crate::drivers::keyboard::set_gui_mode(true);
 
+    // Start an input record/replay session if one was queued or requested
+    // at boot
+    crate::drivers::input_replay::start_requested();
+
     // Render loop: composite -> blit to framebuffer -> poll input -> repeat
     render_loop(&hw, &mut state);
 
+    crate::drivers::input_replay::finish_session();
+
     // Restore keyboard to shell mode (ANSI escape sequences for arrows)
     crate::drivers::keyboard::set_gui_mode(false);
 
@@ -246,8 +256,11 @@ struct DesktopState {
     // Calculator state (owned, integer arithmetic)
     calculator: CalculatorState,
 
-    // Theme engine for color scheme management
-    theme: crate::desktop::desktop_ext::theme::ThemeManager,
+    // Desktop configuration generation last applied to the scene
+    config_generation: u64,
+
+    // Background surface buffer (wallpaper + icons)
+    bg_pool_buf_id: u32,
 
     // Clipboard manager for copy/paste
     clipboard: crate::desktop::desktop_ext::clipboard::ClipboardManager,
@@ -267,10 +280,18 @@ struct DesktopState {
     // Dynamic apps (spawned from launcher, closeable)
     dynamic_apps: alloc::vec::Vec<DynamicApp>,
 
+    // Mouse cursor (hardware plane or software sprite)
+    cursor: cursor::CursorPlane,
+
+    // Vblank the last frame was paced to
+    vblank_seq: u64,
+
     // Input state
     frame_count: u64,
     drag: Option<DragState>,
     prev_focused: Option<u32>,
+    // Last title bar click (window, uptime ms) for double-click detection
+    last_title_click: Option<(u32, u64)>,
 }
 
 impl DesktopState {
@@ -290,14 +311,19 @@ impl DesktopState {
                 return Some(app.surface_id);
             }
         }
-        None
+        crate::desktop::client_window::surface_for_window(wid)
     }
 }
 
 /// Create the initial desktop scene: background gradient, real apps, and panel.
 fn create_desktop_scene(width: u32, height: u32) -> DesktopState {
+    // Pick up /etc/veridian/desktop.toml now that the root filesystem is
+    // mounted; without one the built-in defaults apply.
+    let _ = crate::desktop::config::load();
+    let config_generation = crate::desktop::config::generation();
+
     // --- Background surface ---
-    let bg_surface_id = 1000;
+    let bg_surface_id = BG_SURFACE_ID;
     crate::desktop::wayland::with_display(|display| {
         let _ = display.wl_compositor.create_surface(bg_surface_id);
         display
@@ -307,7 +333,7 @@ fn create_desktop_scene(width: u32, height: u32) -> DesktopState {
 
     let pool_size = (width as usize) * (height as usize) * 4;
     let mut bg_pixels = vec![0u8; pool_size];
-    paint_gradient_background(&mut bg_pixels, width as usize, height as usize);
+    paint_desktop_background(&mut bg_pixels, width as usize, height as usize);
 
     // Render desktop icons into the background surface so they appear behind
     // all windows naturally via compositor z-order.
@@ -315,7 +341,7 @@ fn create_desktop_scene(width: u32, height: u32) -> DesktopState {
     let icon_grid = create_default_icon_grid(width, height - panel_h);
     render_icons_into_bgra(&icon_grid, &mut bg_pixels, width as usize, height as usize);
 
-    let pool_id = 100;
+    let pool_id = BG_POOL_ID;
     let mut pool = crate::desktop::wayland::buffer::WlShmPool::new(pool_id, 0, pool_size);
     pool.write_data(0, &bg_pixels);
     let buf_id = pool
@@ -458,7 +484,8 @@ fn create_desktop_scene(width: u32, height: u32) -> DesktopState {
         settings_app: crate::desktop::settings::SettingsApp::new(),
         image_viewer: crate::desktop::image_viewer::ImageViewer::new(),
         calculator: CalculatorState::new(),
-        theme: crate::desktop::desktop_ext::theme::ThemeManager::new(),
+        config_generation,
+        bg_pool_buf_id: buf_id,
         clipboard: crate::desktop::desktop_ext::clipboard::ClipboardManager::new(),
         dnd: crate::desktop::desktop_ext::dnd::DndManager::new(),
         icon_grid,
@@ -466,8 +493,11 @@ fn create_desktop_scene(width: u32, height: u32) -> DesktopState {
         pdf_page_index: 0,
         dynamic_apps: alloc::vec::Vec::new(),
         frame_count: 0,
+        cursor: cursor::CursorPlane::new(cursor::DEFAULT_THEME, cursor::DEFAULT_SIZE),
+        vblank_seq: crate::graphics::vsync_sw::vblank_seq(),
         drag: None,
         prev_focused: None,
+        last_title_click: None,
     }
 }
 
@@ -552,9 +582,10 @@ fn render_icons_into_bgra(
 
 /// Draw a string into a BGRA pixel buffer at (px, py) with the given color.
 ///
-/// Uses the 8x16 VGA font. Characters are spaced 8 pixels apart horizontally.
-/// CJK wide characters (detected via `cjk::char_width`) advance 16px instead of
-/// 8px.
+/// `text` is UTF-8; malformed sequences show as U+FFFD. Uses the 8x16 VGA
+/// font through [`draw_glyph_into_buffer`]: characters are spaced 8 pixels
+/// apart, CJK wide characters (detected via `cjk::char_width`) advance 16px
+/// and combining marks take no space.
 pub fn draw_string_into_buffer(
     buf: &mut [u8],
     buf_width: usize,
@@ -564,13 +595,44 @@ pub fn draw_string_into_buffer(
     color: u32,
 ) {
     let mut cursor_x = px;
-    for &ch in text.iter() {
-        let w = crate::desktop::desktop_ext::cjk::char_width(ch as char);
-        if w == 0 {
-            continue; // skip zero-width / combining marks
+    for ch in i18n::utf8::chars_lossy(text) {
+        cursor_x += draw_glyph_into_buffer(buf, buf_width, ch, cursor_x, py, color);
+    }
+}
+
+/// Draw one character at (px, py) and return how far it advances.
+///
+/// The glyph comes from `font8x16::glyph_index`. Wide characters, which the
+/// 8x16 font cannot show, get a 16px outlined box so the text keeps its
+/// layout; zero-width characters draw nothing.
+pub fn draw_glyph_into_buffer(
+    buf: &mut [u8],
+    buf_width: usize,
+    ch: char,
+    px: usize,
+    py: usize,
+    color: u32,
+) -> usize {
+    match crate::desktop::desktop_ext::cjk::char_width(ch) {
+        0 => 0,
+        1 => {
+            let index = crate::graphics::font8x16::glyph_index(ch);
+            draw_char_into_buffer(buf, buf_width, index, px, py, color);
+            8
+        }
+        _ => {
+            let [_, r, g, b] = color.to_be_bytes();
+            for y in py + 2..py + 14 {
+                for x in px + 1..px + 15 {
+                    let edge = y == py + 2 || y == py + 13 || x == px + 1 || x == px + 14;
+                    let offset = (y * buf_width + x) * 4;
+                    if edge && offset + 3 < buf.len() {
+                        buf[offset..offset + 4].copy_from_slice(&[b, g, r, 0xFF]);
+                    }
+                }
+            }
+            16
         }
-        draw_char_into_buffer(buf, buf_width, ch, cursor_x, py, color);
-        cursor_x += (w as usize) * 8;
     }
 }
 
@@ -675,23 +737,76 @@ pub fn update_surface_pixels(surface_id: u32, pool_id: u32, pool_buf_id: u32, pi
     });
 }
 
-/// Paint a gradient background into a BGRA pixel buffer.
-fn paint_gradient_background(buf: &mut [u8], width: usize, height: usize) {
-    for y in 0..height {
-        // Vertical gradient from dark blue-grey (#2D3436) to darker (#1a1a2e)
-        // Using integer math (fixed-point 8.8) to avoid soft-float overhead
-        let t256 = (y * 256) / height; // 0..255
-        let inv_t = 256 - t256;
-        let r = ((0x2D * inv_t + 0x1a * t256) / 256) as u8;
-        let g = ((0x34 * inv_t + 0x1a * t256) / 256) as u8;
-        let b = ((0x36 * inv_t + 0x2e * t256) / 256) as u8;
+/// Paint the desktop background into a BGRA pixel buffer: the configured
+/// wallpaper, or a gradient in the theme's desktop color when none is set
+/// or it cannot be loaded.
+fn paint_desktop_background(buf: &mut [u8], width: usize, height: usize) {
+    let wallpaper = crate::desktop::config::with_config(|c| c.wallpaper.clone());
+    if let Some(path) = wallpaper {
+        let image = crate::fs::read_file(&path)
+            .map_err(|_| "cannot read file")
+            .and_then(|data| image_format::decode(&data).map_err(image_format::ImageError::as_str));
+        match image {
+            Ok(image) => {
+                paint_wallpaper(buf, width, height, &image);
+                return;
+            }
+            Err(reason) => {
+                crate::serial::_serial_print(format_args!(
+                    "[DESKTOP] Wallpaper {}: {}\n",
+                    path, reason
+                ));
+            }
+        }
+    }
+    paint_gradient_background(buf, width, height, &crate::desktop::config::colors());
+}
 
+/// Scale `image` to cover the whole buffer, keeping its aspect ratio and
+/// cropping the overflow evenly (nearest-neighbour sampling).
+fn paint_wallpaper(buf: &mut [u8], width: usize, height: usize, image: &image_format::Image) {
+    let (iw, ih) = (image.width as usize, image.height as usize);
+    if iw == 0 || ih == 0 || width == 0 || height == 0 {
+        return;
+    }
+    // Source pixels per screen pixel as num/den: the smaller of the two axis
+    // ratios, so the scaled image is at least as large as the screen.
+    let (num, den) = if iw * height > ih * width {
+        (ih, height)
+    } else {
+        (iw, width)
+    };
+    let x_off = (iw - width * num / den) / 2;
+    let y_off = (ih - height * num / den) / 2;
+
+    for (y, row) in buf.chunks_exact_mut(width * 4).take(height).enumerate() {
+        let sy = (y_off + y * num / den).min(ih - 1);
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            let sx = (x_off + x * num / den).min(iw - 1);
+            let src = (sy * iw + sx) * 4;
+            let rgba = &image.pixels[src..src + 4];
+            px.copy_from_slice(&[rgba[2], rgba[1], rgba[0], 0xFF]);
+        }
+    }
+}
+
+/// Paint a vertical gradient in the theme's desktop color into a BGRA pixel
+/// buffer, with a centered title.
+fn paint_gradient_background(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    colors: &crate::desktop::desktop_ext::theme::ThemeColors,
+) {
+    let top = colors.desktop_background;
+    let bottom = top.darken(40);
+    for y in 0..height {
+        // Integer blend weight (0..255) to avoid soft-float overhead
+        let t256 = ((y * 256) / height) as u32;
+        let pixel = top.blend(bottom, t256).to_bgra();
         for x in 0..width {
             let offset = (y * width + x) * 4;
-            buf[offset] = b; // B
-            buf[offset + 1] = g; // G
-            buf[offset + 2] = r; // R
-            buf[offset + 3] = 0xFF; // A
+            buf[offset..offset + 4].copy_from_slice(&pixel);
         }
     }
 
@@ -700,7 +815,14 @@ fn paint_gradient_background(buf: &mut [u8], width: usize, height: usize) {
     let title_x = (width - title.len() * 8) / 2;
     let title_y = height / 2 - 20;
     for (i, &ch) in title.iter().enumerate() {
-        draw_char_into_buffer(buf, width, ch, title_x + i * 8, title_y, 0xAAAAAA);
+        draw_char_into_buffer(
+            buf,
+            width,
+            ch,
+            title_x + i * 8,
+            title_y,
+            colors.text_secondary.rgb(),
+        );
     }
 
     // Draw subtitle
@@ -708,10 +830,40 @@ fn paint_gradient_background(buf: &mut [u8], width: usize, height: usize) {
     let sub_x = (width - sub.len() * 8) / 2;
     let sub_y = height / 2 + 4;
     for (i, &ch) in sub.iter().enumerate() {
-        draw_char_into_buffer(buf, width, ch, sub_x + i * 8, sub_y, 0x666666);
+        draw_char_into_buffer(
+            buf,
+            width,
+            ch,
+            sub_x + i * 8,
+            sub_y,
+            colors.text_disabled.rgb(),
+        );
     }
 }
 
+/// Repaint the background surface (wallpaper and desktop icons).
+fn repaint_background(state: &DesktopState, width: u32, height: u32) {
+    let mut pixels = vec![0u8; (width as usize) * (height as usize) * 4];
+    paint_desktop_background(&mut pixels, width as usize, height as usize);
+    render_icons_into_bgra(
+        &state.icon_grid,
+        &mut pixels,
+        width as usize,
+        height as usize,
+    );
+    update_surface_pixels(BG_SURFACE_ID, BG_POOL_ID, state.bg_pool_buf_id, &pixels);
+}
+
+/// Bring the scene in line with a changed desktop configuration. Apps and
+/// decorations read their colors every frame; the background and the
+/// terminals' default colors are cached and need an explicit refresh.
+fn apply_desktop_config(state: &mut DesktopState, layout: &FrameLayout) {
+    repaint_background(state, layout.fb_width as u32, layout.fb_height as u32);
+    let colors = crate::desktop::config::colors();
+    crate::desktop::terminal::with_terminal_manager(|tm| tm.apply_theme(&colors));
+    render_panel(state, layout.fb_width as u32);
+}
+
 /// Translate a raw input_event::InputEvent to a window_manager::InputEvent.
 fn translate_input_event(
     raw: &crate::drivers::input_event::InputEvent,
@@ -767,6 +919,12 @@ fn translate_input_event(
                 None
             }
         }
+        // Absolute pointer input (touch, injected): the cursor is already
+        // positioned, so report a move once per X/Y pair as for EV_REL
+        EV_ABS if raw.code == ABS_X => Some(WmEvent::MouseMove {
+            x: mouse_x,
+            y: mouse_y,
+        }),
         _ => None,
     }
 }
@@ -861,6 +1019,10 @@ fn close_dynamic_app(state: &mut DesktopState, wid: u32) {
 
 /// Close any window (dynamic or static) by WM window ID.
 fn close_any_window(state: &mut DesktopState, wid: u32) {
+    // Client windows: the owning process decides
+    if crate::desktop::client_window::request_close(wid) {
+        return;
+    }
     // Dynamic apps: use existing close path
     if state.dynamic_apps.iter().any(|a| a.wid == wid) {
         close_dynamic_app(state, wid);
@@ -904,7 +1066,8 @@ fn close_any_window(state: &mut DesktopState, wid: u32) {
 /// surface pixel buffer.  The title bar occupies the top 28 rows of the
 /// buffer.  The window's title and focus state are read from the WM.
 pub fn draw_title_bar_into_surface(pixels: &mut [u8], width: usize, _total_h: usize, wid: u32) {
-    let cfg = DecorationConfig::default_config();
+    let colors = crate::desktop::config::colors();
+    let cfg = DecorationConfig::from_theme(&colors);
     let tbh = cfg.title_bar_height as usize;
 
     // Look up window title and focus state
@@ -940,19 +1103,23 @@ pub fn draw_title_bar_into_surface(pixels: &mut [u8], width: usize, _total_h: us
     }
 
     // Draw title text (vertically centered in title bar)
-    let text_color = cfg.title_text_color & 0x00FF_FFFF; // strip alpha for draw helper
+    let text_color = if focused {
+        colors.titlebar_foreground.rgb()
+    } else {
+        colors.titlebar_foreground_inactive.rgb()
+    };
     let text_y = (tbh.saturating_sub(16)) / 2;
     for (ci, &ch) in title_buf[..title_len].iter().enumerate() {
         draw_char_into_buffer(pixels, width, ch, 8 + ci * 8, text_y, text_color);
     }
 
-    // Draw close button (red square with white X) at top-right
+    // Draw close button (theme error color with a white X) at top-right
     let btn_sz = 16usize;
     let btn_x = width.saturating_sub(22);
     let btn_y = (tbh.saturating_sub(btn_sz)) / 2;
-    let close_r: u8 = 0xE7;
-    let close_g: u8 = 0x4C;
-    let close_b: u8 = 0x3C;
+    let close_r = colors.error.red();
+    let close_g = colors.error.green();
+    let close_b = colors.error.blue();
     for dy in 0..btn_sz {
         for dx in 0..btn_sz {
             let off = ((btn_y + dy) * width + btn_x + dx) * 4;
@@ -1113,6 +1280,13 @@ fn render_loop(hw: &fbcon::FbHwInfo, state: &mut DesktopState) {
     // Do initial render of all app surfaces
     render_all_apps(state);
 
+    // The virtio-gpu cursor plane sits on the scanout that try_gpu_blit()
+    // presents to, so it can replace the software cursor there.
+    #[cfg(target_arch = "x86_64")]
+    state
+        .cursor
+        .set_hardware(crate::drivers::virtio_gpu::has_cursor_plane());
+
     // Set screen size for window manager placement heuristics
     crate::desktop::window_manager::with_window_manager(|wm| {
         wm.set_screen_size(layout.fb_width as u32, layout.fb_height as u32);
@@ -1137,7 +1311,7 @@ fn render_loop(hw: &fbcon::FbHwInfo, state: &mut DesktopState) {
         update_ui_state(state, tick);
 
         // Render apps, composite overlays, blit to framebuffer
-        render_and_composite(state, &layout, tick);
+        render_and_composite(state, &layout);
     }
 }
 
@@ -1153,6 +1327,10 @@ fn handle_screen_lock(state: &mut DesktopState, layout: &FrameLayout, tick: u64)
         }
     }
     state.screen_locker.tick(tick);
+    if !state.screen_locker.is_locked() {
+        crate::serial::_serial_print(format_args!("[DESKTOP] Screen unlocked\n"));
+        return;
+    }
     crate::desktop::wayland::with_display(|display| {
         display.wl_compositor.with_back_buffer_mut(|bb| {
             state
@@ -1168,9 +1346,7 @@ fn handle_screen_lock(state: &mut DesktopState, layout: &FrameLayout, tick: u64)
             layout.is_bgr,
         );
     });
-    for _ in 0..50_000 {
-        core::hint::spin_loop();
-    }
+    state.vblank_seq = crate::graphics::vsync_sw::wait_for_vblank(state.vblank_seq);
 }
 
 /// Poll hardware input, translate events, and dispatch hotkeys/mouse/keyboard.
@@ -1232,6 +1408,7 @@ fn handle_input_events(state: &mut DesktopState, layout: &FrameLayout) -> bool {
                 == (crate::drivers::keyboard::MOD_CTRL | crate::drivers::keyboard::MOD_ALT)
         {
             state.screen_locker.lock();
+            crate::serial::_serial_print(format_args!("[DESKTOP] Screen locked\n"));
             continue;
         }
 
@@ -1356,6 +1533,11 @@ fn dispatch_mouse_and_keyboard(
             crate::desktop::launcher::with_launcher(|l| l.hide());
         }
 
+        // Toasts sit above everything else
+        if crate::desktop::notification::handle_click(x, y) {
+            return;
+        }
+
         // Panel click
         if y >= layout.panel_y {
             crate::desktop::panel::with_panel(|p| p.update_buttons());
@@ -1377,10 +1559,14 @@ fn dispatch_mouse_and_keyboard(
                 .flatten();
 
         if let Some(wid) = hit {
-            crate::desktop::window_manager::with_window_manager(|wm| {
-                let _ = wm.focus_window(wid);
-            });
-            sync_compositor_focus(state, wid);
+            // On-screen keyboards and similar windows leave the focus
+            // where the user is typing
+            if crate::desktop::client_window::takes_focus(wid) {
+                crate::desktop::window_manager::with_window_manager(|wm| {
+                    let _ = wm.focus_window(wid);
+                });
+                sync_compositor_focus(state, wid);
+            }
 
             let in_title_bar = crate::desktop::window_manager::with_window_manager(|wm| {
                 wm.get_window(wid).map(|w| y < w.y + TITLE_BAR_HEIGHT)
@@ -1400,6 +1586,19 @@ fn dispatch_mouse_and_keyboard(
                     return;
                 }
 
+                // A second click within the double-click time toggles
+                // maximize
+                let now = crate::timer::get_uptime_ms();
+                let double_click_ms =
+                    crate::desktop::config::with_config(|c| c.double_click_ms) as u64;
+                if let Some((last_wid, last_ms)) = state.last_title_click.take() {
+                    if last_wid == wid && now.saturating_sub(last_ms) <= double_click_ms {
+                        toggle_maximize(state, wid);
+                        return;
+                    }
+                }
+                state.last_title_click = Some((wid, now));
+
                 // Start drag
                 if let Some(surface_id) = state.surface_for_window(wid) {
                     let win_pos = crate::desktop::window_manager::with_window_manager(|wm| {
@@ -1524,6 +1723,30 @@ fn dispatch_mouse_and_keyboard(
     });
 }
 
+/// Maximize a window, or restore it if it already is.
+fn toggle_maximize(state: &DesktopState, wid: u32) {
+    let Some(surface_id) = state.surface_for_window(wid) else {
+        return;
+    };
+    crate::desktop::window_manager::with_window_manager(|wm| {
+        let zone = match wm.get_window(wid).map(|w| w.snap_zone) {
+            Some(crate::desktop::window_manager::SnapZone::Maximize) => {
+                crate::desktop::window_manager::SnapZone::None
+            }
+            Some(_) => crate::desktop::window_manager::SnapZone::Maximize,
+            None => return,
+        };
+        wm.snap_window(wid, zone);
+        if let Some(w) = wm.get_window(wid) {
+            crate::desktop::wayland::with_display(|display| {
+                display
+                    .wl_compositor
+                    .set_surface_position(surface_id, w.x, w.y);
+            });
+        }
+    });
+}
+
 /// Update focus tracking, idle timeout, animations, and notifications.
 fn update_ui_state(state: &mut DesktopState, tick: u64) {
     // Detect focus changes and sync compositor z_order
@@ -1540,6 +1763,7 @@ fn update_ui_state(state: &mut DesktopState, tick: u64) {
     // Check idle timeout for screen lock
     if state.screen_locker.check_idle_timeout(tick) {
         state.screen_locker.lock();
+        crate::serial::_serial_print(format_args!("[DESKTOP] Screen locked (idle)\n"));
     }
 
     // Forward queued WM events to apps
@@ -1556,14 +1780,19 @@ fn update_ui_state(state: &mut DesktopState, tick: u64) {
 
     // Tick notification expiry (every 30th frame to avoid overhead)
     if state.frame_count.is_multiple_of(30) {
-        crate::desktop::notification::with_notification_manager(|mgr| {
-            mgr.tick(tick);
-        });
+        crate::desktop::notification::tick();
     }
 }
 
 /// Render all apps, composite overlays, and blit to the hardware framebuffer.
-fn render_and_composite(state: &mut DesktopState, layout: &FrameLayout, tick: u64) {
+fn render_and_composite(state: &mut DesktopState, layout: &FrameLayout) {
+    // Settings changed from the settings app, the shell or over IPC
+    let generation = crate::desktop::config::generation();
+    if generation != state.config_generation {
+        state.config_generation = generation;
+        apply_desktop_config(state, layout);
+    }
+
     render_all_apps(state);
 
     // Panel: update clock + buttons + systray periodically
@@ -1572,13 +1801,13 @@ fn render_and_composite(state: &mut DesktopState, layout: &FrameLayout, tick: u6
     }
 
     // Composite, render overlays, and blit
-    crate::desktop::wayland::with_display(|display| {
+    let composited = crate::desktop::wayland::with_display(|display| {
         display.wl_compositor.request_composite();
         let composited = display.wl_compositor.composite().unwrap_or(false);
 
         if composited {
             display.wl_compositor.with_back_buffer_mut(|bb| {
-                render_overlays(state, bb, layout.fb_width, layout.fb_height, tick);
+                render_overlays(state, bb, layout.fb_width, layout.fb_height);
             });
 
             blit_back_buffer(
@@ -1590,27 +1819,41 @@ fn render_and_composite(state: &mut DesktopState, layout: &FrameLayout, tick: u6
                 layout.is_bgr,
             );
         }
-    });
+        composited
+    })
+    .unwrap_or(false);
 
-    // Always draw cursor (cheap: 16x16 pixels) so it stays responsive
+    if composited {
+        crate::desktop::screenshot::on_frame();
+    }
+
+    // The cursor is not part of the composited scene: the hardware plane
+    // just moves, and the software sprite only touches its own rects
+    // unless the blit above repainted what was under it.
+    let shape = if state.drag.is_some() {
+        cursor::CursorShape::Move
+    } else {
+        cursor::CursorShape::Default
+    };
+    state.cursor.set_shape(shape);
     let (mouse_x, mouse_y) = crate::drivers::mouse::cursor_position();
     // SAFETY: fb_ptr is valid for stride * height bytes.
     let fb_slice = unsafe {
         core::slice::from_raw_parts_mut(layout.fb_ptr, layout.fb_stride * layout.fb_height)
     };
-    cursor::draw_cursor(
-        fb_slice,
-        layout.fb_stride,
-        layout.fb_width,
-        layout.fb_height,
-        mouse_x,
-        mouse_y,
-    );
+    let mut target = cursor::CursorTarget {
+        buf: fb_slice,
+        stride: layout.fb_stride,
+        width: layout.fb_width,
+        height: layout.fb_height,
+        is_bgr: layout.is_bgr,
+    };
+    state
+        .cursor
+        .present(mouse_x, mouse_y, &mut target, composited);
 
-    // Yield CPU -- short spin for frame pacing
-    for _ in 0..5_000 {
-        core::hint::spin_loop();
-    }
+    // Sleep until the next vblank so we composite once per refresh
+    state.vblank_seq = crate::graphics::vsync_sw::wait_for_vblank(state.vblank_seq);
 }
 
 /// Render modal overlays into the composited back-buffer (post-composite,
@@ -1619,13 +1862,7 @@ fn render_and_composite(state: &mut DesktopState, layout: &FrameLayout, tick: u6
 /// Only true overlays (app switcher, launcher, notifications) are drawn here.
 /// Title bars, close buttons, and desktop icons are now part of their
 /// respective surfaces.
-fn render_overlays(
-    state: &DesktopState,
-    bb: &mut [u32],
-    fb_width: usize,
-    fb_height: usize,
-    tick: u64,
-) {
+fn render_overlays(state: &DesktopState, bb: &mut [u32], fb_width: usize, fb_height: usize) {
     // Title bars and close buttons are rendered into each surface's pixel
     // buffer (see draw_title_bar_into_surface), and desktop icons are baked
     // into the background surface (see render_icons_into_bgra).  The
@@ -1646,17 +1883,14 @@ fn render_overlays(
     });
 
     // Notification toasts (top-right)
-    crate::desktop::notification::with_notification_manager(|mgr| {
-        if mgr.active_count() > 0 {
-            mgr.render_to_buffer(bb, fb_width, fb_height, tick);
-        }
-    });
+    crate::desktop::notification::render_overlay(bb, fb_width, fb_height);
 }
 
 /// Try to blit the compositor back-buffer via VirtIO GPU (DMA path).
 ///
-/// Returns `true` if the GPU handled the blit, `false` to fall back to
-/// direct MMIO writes.
+/// The frame goes into the GPU's back buffer and is flipped onto the
+/// scanout, so the host never shows a partly copied frame. Returns `true` if
+/// the GPU handled the blit, `false` to fall back to direct MMIO writes.
 #[cfg(target_arch = "x86_64")]
 fn try_gpu_blit(compositor: &crate::desktop::wayland::compositor::Compositor) -> bool {
     if !crate::drivers::virtio_gpu::is_available() {
@@ -1664,12 +1898,12 @@ fn try_gpu_blit(compositor: &crate::desktop::wayland::compositor::Compositor) ->
     }
     crate::drivers::virtio_gpu::with_driver(|gpu| {
         compositor.with_back_buffer(|bb| {
-            if let Some(backing) = gpu.get_framebuffer_mut() {
+            if let Some(backing) = gpu.back_buffer_mut() {
                 let copy_len = bb.len().min(backing.len());
                 backing[..copy_len].copy_from_slice(&bb[..copy_len]);
             }
         });
-        let _ = gpu.flush_framebuffer();
+        let _ = gpu.flip();
     });
     true
 }
@@ -1797,8 +2031,9 @@ fn render_all_apps(state: &mut DesktopState) {
         });
     }
 
-    // File manager
+    // File manager (sizes are computed a slice at a time between frames)
     crate::desktop::file_manager::with_file_manager(|fm| {
+        fm.write().poll_background();
         fm.read().render_to_surface();
     });
 
@@ -1808,9 +2043,10 @@ fn render_all_apps(state: &mut DesktopState) {
     });
 
     // Compute themed background color for dynamic apps (strip alpha byte)
-    let app_bg = state.theme.colors().window_background.0 & 0x00FFFFFF;
-    let app_error_color = state.theme.colors().error.0 & 0x00FFFFFF;
-    let app_accent_color = state.theme.colors().accent.0 & 0x00FFFFFF;
+    let colors = crate::desktop::config::colors();
+    let app_bg = colors.window_background.rgb();
+    let app_error_color = colors.error.rgb();
+    let app_accent_color = colors.accent.rgb();
 
     // Dynamic apps -- render content then prepend title bar into full surface
     let title_bar_h: usize = 28;
@@ -1868,6 +2104,9 @@ fn render_all_apps(state: &mut DesktopState) {
 
         update_surface_pixels(app.surface_id, app.pool_id, app.pool_buf_id, &pixels);
     }
+
+    // Windows of user processes
+    crate::desktop::client_window::update(state.panel_surface_id);
 }
 
 /// Render a placeholder app with title text on a solid background.
@@ -2475,13 +2714,14 @@ fn render_panel(state: &DesktopState, screen_width: u32) {
             tray.update_volume(vol_pct);
         }
 
-        // Battery: check power state
-        let battery_label = if crate::power::is_initialized() {
-            "AC"
+        // Battery: charge while a battery is present, else mains power
+        let power = crate::power::battery::status();
+        let battery_label = if power.battery_present {
+            alloc::format!("{}%", power.capacity_percent)
         } else {
-            "--"
+            alloc::string::String::from("AC")
         };
-        tray.update_battery(battery_label);
+        tray.update_battery(&battery_label);
     });
 
     let panel_h = crate::desktop::panel::PANEL_HEIGHT;
@@ -2680,6 +2920,9 @@ fn forward_events_to_apps(state: &mut DesktopState) {
             close_dynamic_app(state, wid);
         }
     }
+
+    // Client windows queue their events for the owning process
+    crate::desktop::client_window::forward_events();
 }
 
 /// Blit the compositor's XRGB8888 back-buffer to the hardware framebuffer.
@@ -2773,6 +3016,20 @@ impl DecorationConfig {
     }
 }
 
+impl DecorationConfig {
+    /// Decorations in the colors of a theme.
+    pub fn from_theme(colors: &crate::desktop::desktop_ext::theme::ThemeColors) -> Self {
+        Self {
+            title_bg_focused: colors.titlebar_background.0,
+            title_bg_unfocused: colors.titlebar_background_inactive.0,
+            title_text_color: colors.titlebar_foreground.0,
+            border_focused: colors.window_border_focused.0,
+            border_unfocused: colors.window_border.0,
+            ..Self::default_config()
+        }
+    }
+}
+
 impl Default for DecorationConfig {
     fn default() -> Self {
         Self::default_config()
@@ -0,0 +1,400 @@
+//! Screenshot capture, comparison, and screen recording.
+//!
+//! Captures the compositor back-buffer (the last composited frame), either
+//! whole or cropped to one window, and encodes it as PNG, BMP or QOI with
+//! the shared `image-format` codecs. Saved screenshots can be loaded back
+//! and compared pixel-by-pixel, which is what automated UI tests built on
+//! [`crate::drivers::input_replay`] use to check each checkpoint against a
+//! reference image.
+//!
+//! A recording writes every Nth composited frame to a directory as a
+//! numbered PNG sequence (`frame-00000.png`, ...). The cursor plane is not
+//! part of the back-buffer, so neither screenshots nor recordings show the
+//! pointer.
+
+use alloc::{format, string::String, vec::Vec};
+
+use image_format::{Format, Image, ImageError};
+use spin::Mutex;
+
+use crate::error::KernelError;
+
+/// Composited frames between recorded frames when none is given (about
+/// 10 frames per second at the renderer's 60 Hz).
+pub const DEFAULT_RECORD_INTERVAL: u32 = 6;
+
+/// A captured frame in the compositor's native `0x00RRGGBB` format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+impl Screenshot {
+    /// Convert to an opaque RGBA image.
+    pub fn to_image(&self) -> Image {
+        let argb: Vec<u32> = self.pixels.iter().map(|px| px | 0xFF00_0000).collect();
+        Image::from_argb(self.width, self.height, &argb)
+    }
+
+    /// Convert from an RGBA image, dropping the alpha channel.
+    pub fn from_image(image: &Image) -> Self {
+        Self {
+            width: image.width,
+            height: image.height,
+            pixels: image
+                .to_argb()
+                .into_iter()
+                .map(|px| px & 0x00FF_FFFF)
+                .collect(),
+        }
+    }
+
+    /// Encode in `format`.
+    pub fn encode(&self, format: Format) -> Result<Vec<u8>, KernelError> {
+        image_format::encode(&self.to_image(), format).map_err(image_error)
+    }
+
+    /// Encode as a 32bpp top-down BMP.
+    pub fn to_bmp(&self) -> Result<Vec<u8>, KernelError> {
+        self.encode(Format::Bmp)
+    }
+
+    /// Encode as an 8-bit RGB PNG.
+    pub fn to_png(&self) -> Result<Vec<u8>, KernelError> {
+        self.encode(Format::Png)
+    }
+
+    /// Decode a PNG, BMP or QOI image.
+    pub fn decode(data: &[u8]) -> Result<Self, KernelError> {
+        image_format::decode(data)
+            .map(|image| Self::from_image(&image))
+            .map_err(image_error)
+    }
+
+    /// Number of pixels that differ from `other` (ignoring the unused top
+    /// byte), or `None` if the dimensions differ.
+    pub fn diff(&self, other: &Screenshot) -> Option<usize> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+        Some(
+            self.pixels
+                .iter()
+                .zip(&other.pixels)
+                .filter(|(a, b)| (*a ^ *b) & 0x00FF_FFFF != 0)
+                .count(),
+        )
+    }
+
+    /// The part of the screenshot inside the `width` x `height` rectangle
+    /// at `(x, y)`, clipped to the screenshot. `None` if nothing is left.
+    pub fn crop(&self, x: i32, y: i32, width: u32, height: u32) -> Option<Screenshot> {
+        let left = x.max(0) as u32;
+        let top = y.max(0) as u32;
+        let right = (x as i64 + width as i64).clamp(0, self.width as i64) as u32;
+        let bottom = (y as i64 + height as i64).clamp(0, self.height as i64) as u32;
+        if left >= right || top >= bottom {
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity(((right - left) * (bottom - top)) as usize);
+        for row in top..bottom {
+            let start = (row * self.width + left) as usize;
+            pixels.extend_from_slice(&self.pixels[start..start + (right - left) as usize]);
+        }
+        Some(Screenshot {
+            width: right - left,
+            height: bottom - top,
+            pixels,
+        })
+    }
+
+    /// Write the screenshot to `path` in the format its extension names
+    /// (`.bmp`, `.qoi`), PNG otherwise. Returns bytes written.
+    pub fn save(&self, path: &str) -> Result<usize, KernelError> {
+        let format = Format::from_path(path).unwrap_or(Format::Png);
+        crate::fs::write_file(path, &self.encode(format)?)
+    }
+
+    /// Load a PNG, BMP or QOI screenshot from `path`.
+    pub fn load(path: &str) -> Result<Self, KernelError> {
+        Self::decode(&crate::fs::read_file(path)?)
+    }
+}
+
+fn image_error(err: ImageError) -> KernelError {
+    KernelError::InvalidArgument {
+        name: "image",
+        value: err.as_str(),
+    }
+}
+
+/// Capture the most recently composited frame.
+///
+/// Fails with `InvalidState` if the desktop has not configured an output.
+pub fn capture() -> Result<Screenshot, KernelError> {
+    let not_running = KernelError::InvalidState {
+        expected: "desktop running",
+        actual: "no compositor output",
+    };
+    crate::desktop::wayland::with_display(|d| {
+        let (width, height) = d.wl_compositor.output_size();
+        if width == 0 || height == 0 {
+            return Err(not_running);
+        }
+        let mut pixels = d.wl_compositor.back_buffer();
+        pixels.resize((width * height) as usize, 0);
+        Ok(Screenshot {
+            width,
+            height,
+            pixels,
+        })
+    })
+    .unwrap_or(Err(not_running))
+}
+
+/// Capture window `wid` as it currently appears on screen.
+///
+/// The window's rectangle is cut out of the composited frame, so anything
+/// overlapping the window is captured too.
+pub fn capture_window(wid: u32) -> Result<Screenshot, KernelError> {
+    let window = crate::desktop::window_manager::with_window_manager(|wm| wm.get_window(wid))
+        .flatten()
+        .ok_or(KernelError::NotFound {
+            resource: "window",
+            id: wid as u64,
+        })?;
+    capture()?
+        .crop(window.x, window.y, window.width, window.height)
+        .ok_or(KernelError::InvalidState {
+            expected: "window on screen",
+            actual: "window off screen",
+        })
+}
+
+/// Capture the whole screen (`None`) or one window.
+fn capture_target(window: Option<u32>) -> Result<Screenshot, KernelError> {
+    match window {
+        Some(wid) => capture_window(wid),
+        None => capture(),
+    }
+}
+
+/// Capture the screen or `window` and write it to `path` (see
+/// [`Screenshot::save`]). Returns bytes written.
+pub fn save_screenshot(path: &str, window: Option<u32>) -> Result<usize, KernelError> {
+    capture_target(window)?.save(path)
+}
+
+// ---------------------------------------------------------------------------
+// Screen recording
+// ---------------------------------------------------------------------------
+
+/// An active frame-sequence recording.
+struct Recording {
+    /// Directory receiving `frame-NNNNN.png`.
+    dir: String,
+    /// Window to record, or the whole screen.
+    window: Option<u32>,
+    /// Record every `interval`-th composited frame.
+    interval: u32,
+    /// Stop after this many frames (0 = until stopped).
+    max_frames: u32,
+    /// Frames written so far.
+    frames: u32,
+    /// Composited frames left until the next recorded one.
+    countdown: u32,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Start recording every `interval`-th composited frame (0 = the default
+/// interval) of the screen or `window` into `dir`, creating it if needed.
+///
+/// Fails with `AlreadyExists` if a recording is already running.
+pub fn start_recording(
+    dir: &str,
+    window: Option<u32>,
+    interval: u32,
+    max_frames: u32,
+) -> Result<(), KernelError> {
+    let mut recording = RECORDING.lock();
+    if recording.is_some() {
+        return Err(KernelError::AlreadyExists {
+            resource: "screen recording",
+            id: 0,
+        });
+    }
+
+    let _ = crate::fs::get_vfs()
+        .read()
+        .mkdir(dir, crate::fs::Permissions::default());
+    if !crate::fs::file_exists(dir) {
+        return Err(KernelError::NotFound {
+            resource: "recording directory",
+            id: 0,
+        });
+    }
+
+    *recording = Some(Recording {
+        dir: String::from(dir.trim_end_matches('/')),
+        window,
+        interval: if interval == 0 {
+            DEFAULT_RECORD_INTERVAL
+        } else {
+            interval
+        },
+        max_frames,
+        frames: 0,
+        countdown: 0,
+    });
+    Ok(())
+}
+
+/// Stop the recording. Returns the number of frames written, or `None` if
+/// nothing was being recorded.
+pub fn stop_recording() -> Option<u32> {
+    RECORDING.lock().take().map(|r| r.frames)
+}
+
+/// Frames written so far by the running recording.
+pub fn recording_frames() -> Option<u32> {
+    RECORDING.lock().as_ref().map(|r| r.frames)
+}
+
+/// Record the frame just composited if the recording is due for one.
+///
+/// Called by the desktop renderer after every composite. A frame that
+/// cannot be captured or written ends the recording.
+pub fn on_frame() {
+    let (path, window) = {
+        let mut guard = RECORDING.lock();
+        let Some(recording) = guard.as_mut() else {
+            return;
+        };
+        if recording.countdown > 0 {
+            recording.countdown -= 1;
+            return;
+        }
+        recording.countdown = recording.interval - 1;
+        let path = format!("{}/frame-{:05}.png", recording.dir, recording.frames);
+        (path, recording.window)
+    };
+
+    // Encode without holding the lock; it takes a while for a full screen
+    let result = capture_target(window).and_then(|shot| shot.save(&path));
+
+    let mut guard = RECORDING.lock();
+    let Some(recording) = guard.as_mut() else {
+        return;
+    };
+    match result {
+        Ok(_) => {
+            recording.frames += 1;
+            if recording.max_frames != 0 && recording.frames >= recording.max_frames {
+                crate::println!(
+                    "[SCREENSHOT] Recording finished: {} frames in {}",
+                    recording.frames,
+                    recording.dir
+                );
+                *guard = None;
+            }
+        }
+        Err(e) => {
+            crate::println!("[SCREENSHOT] Recording stopped: {}: {:?}", path, e);
+            *guard = None;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_bmp_roundtrip() {
+        let shot = Screenshot {
+            width: 3,
+            height: 2,
+            pixels: vec![
+                0x00FF_0000,
+                0x0000_FF00,
+                0x0000_00FF,
+                0,
+                0x00FF_FFFF,
+                0x0012_3456,
+            ],
+        };
+        let bmp = shot.to_bmp().unwrap();
+        assert_eq!(bmp.len(), image_format::bmp::HEADER_SIZE + 6 * 4);
+        assert_eq!(Screenshot::decode(&bmp).unwrap(), shot);
+    }
+
+    #[test]
+    fn test_bmp_bottom_up() {
+        let shot = Screenshot {
+            width: 1,
+            height: 2,
+            pixels: vec![1, 2],
+        };
+        let mut bmp = shot.to_bmp().unwrap();
+        bmp[22..26].copy_from_slice(&2i32.to_le_bytes());
+        assert_eq!(Screenshot::decode(&bmp).unwrap().pixels, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_crop_clips_to_bounds() {
+        let shot = Screenshot {
+            width: 3,
+            height: 3,
+            pixels: (0..9).collect(),
+        };
+        let inner = shot.crop(1, 1, 5, 5).unwrap();
+        assert_eq!((inner.width, inner.height), (2, 2));
+        assert_eq!(inner.pixels, vec![4, 5, 7, 8]);
+
+        let corner = shot.crop(-1, -1, 2, 2).unwrap();
+        assert_eq!(corner.pixels, vec![0]);
+        assert!(shot.crop(3, 0, 1, 1).is_none());
+    }
+
+    #[test]
+    fn test_png_encoding() {
+        let shot = Screenshot {
+            width: 2,
+            height: 1,
+            pixels: vec![0x0012_3456, 0x00FF_0080],
+        };
+        let png = shot.to_png().unwrap();
+        let image = image_format::decode_png(&png).unwrap();
+        assert_eq!(
+            image.pixels,
+            vec![0x12, 0x34, 0x56, 0xFF, 0xFF, 0x00, 0x80, 0xFF]
+        );
+        assert_eq!(Screenshot::decode(&png).unwrap(), shot);
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = Screenshot {
+            width: 2,
+            height: 1,
+            pixels: vec![0x0011_2233, 0x0044_5566],
+        };
+        let mut b = a.clone();
+        b.pixels[0] |= 0xFF00_0000; // alpha/unused byte is ignored
+        assert_eq!(a.diff(&b), Some(0));
+        b.pixels[1] = 0;
+        assert_eq!(a.diff(&b), Some(1));
+        let c = Screenshot {
+            width: 1,
+            height: 2,
+            pixels: vec![0, 0],
+        };
+        assert_eq!(a.diff(&c), None);
+    }
+}
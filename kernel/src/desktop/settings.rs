@@ -7,7 +7,11 @@
 
 use alloc::{format, string::String, vec, vec::Vec};
 
-use super::renderer::draw_string_into_buffer;
+use super::{
+    config::{self, Setting},
+    desktop_ext::theme::ThemePreset,
+    renderer::draw_string_into_buffer,
+};
 
 // ---------------------------------------------------------------------------
 // Settings panel categories
@@ -158,6 +162,16 @@ pub enum PanelPosition {
     Bottom,
 }
 
+/// Themes the Appearance panel cycles through, indexed by `theme_index`.
+const THEMES: [ThemePreset; 6] = [
+    ThemePreset::Dark,
+    ThemePreset::Light,
+    ThemePreset::SolarizedDark,
+    ThemePreset::SolarizedLight,
+    ThemePreset::Nord,
+    ThemePreset::Dracula,
+];
+
 /// Appearance settings.
 #[derive(Debug, Clone)]
 pub struct AppearanceSettings {
@@ -168,10 +182,16 @@ pub struct AppearanceSettings {
 }
 
 impl Default for AppearanceSettings {
+    /// Theme and font size come from the desktop configuration.
     fn default() -> Self {
+        let (theme, font_size) = config::with_config(|c| (c.theme, c.font_size));
         Self {
-            theme_index: 0,
-            font_size: 16,
+            // A custom theme shows as one past the presets
+            theme_index: THEMES
+                .iter()
+                .position(|&t| t == theme)
+                .unwrap_or(THEMES.len()),
+            font_size,
             show_desktop_icons: true,
             panel_position: PanelPosition::Bottom,
         }
@@ -512,8 +532,10 @@ impl SettingsApp {
             SettingsPanel::Appearance => match self.selected_item {
                 0 => {
                     // Cycle theme (0=dark, 1=light, 2=solarized-dark, 3=solarized-light, 4=nord,
-                    // 5=dracula)
-                    self.appearance.theme_index = (self.appearance.theme_index + 1) % 6;
+                    // 5=dracula); from a custom theme start over at dark
+                    let next = (self.appearance.theme_index + 1).min(THEMES.len()) % THEMES.len();
+                    self.appearance.theme_index = next;
+                    let _ = config::set(Setting::Theme, THEMES[next].name());
                 }
                 1 => {
                     // Cycle font size 12..20
@@ -522,6 +544,8 @@ impl SettingsApp {
                     } else {
                         self.appearance.font_size + 2
                     };
+                    let size = format!("{}", self.appearance.font_size);
+                    let _ = config::set(Setting::FontSize, &size);
                 }
                 2 => {
                     self.appearance.show_desktop_icons = !self.appearance.show_desktop_icons;
@@ -2,6 +2,9 @@
 //!
 //! Combines PTY, font rendering, and window manager to provide a graphical
 //! terminal.
+//!
+//! Output is decoded as UTF-8 (malformed bytes show as U+FFFD). Wide (CJK)
+//! characters take two cells and combining marks none.
 
 #[allow(unused_imports)]
 use alloc::{format, string::String, vec, vec::Vec};
@@ -9,9 +12,16 @@ use alloc::{format, string::String, vec, vec::Vec};
 use spin::RwLock;
 
 use crate::{
-    desktop::window_manager::{with_window_manager, InputEvent, WindowId},
+    desktop::{
+        desktop_ext::{
+            cjk,
+            theme::{ThemeColor, ThemeColors},
+        },
+        window_manager::{with_window_manager, InputEvent, WindowId},
+    },
     error::KernelError,
     fs::pty::with_pty_manager,
+    services::locale::{tr, tr_fmt},
     sync::once_lock::GlobalState,
 };
 
@@ -19,8 +29,11 @@ use crate::{
 const TERMINAL_COLS: usize = 80;
 const TERMINAL_ROWS: usize = 24;
 
+/// Placeholder in the cell covered by the right half of a wide character.
+const WIDE_TAIL: char = '\0';
+
 /// Terminal colors
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -42,6 +55,16 @@ impl Color {
     };
 }
 
+impl From<ThemeColor> for Color {
+    fn from(color: ThemeColor) -> Self {
+        Color {
+            r: color.red(),
+            g: color.green(),
+            b: color.blue(),
+        }
+    }
+}
+
 /// Terminal cell
 #[derive(Debug, Clone, Copy)]
 struct Cell {
@@ -151,6 +174,9 @@ pub struct TerminalEmulator {
     /// Maximum scrollback lines
     max_scrollback: usize,
 
+    /// UTF-8 decoder for output in normal (non-escape) mode
+    utf8: i18n::utf8::Decoder,
+
     /// ANSI escape parser state
     esc_state: EscapeState,
     /// ESC sequence parameter accumulator
@@ -185,6 +211,11 @@ impl TerminalEmulator {
                 actual: "uninitialized",
             })??;
 
+        // Default colors follow the desktop theme
+        let colors = crate::desktop::config::colors();
+        let fg = Color::from(colors.window_foreground);
+        let bg = Color::from(colors.window_background);
+
         // Initialize buffer
         let mut buffer = Vec::new();
         for _ in 0..TERMINAL_ROWS {
@@ -206,12 +237,13 @@ impl TerminalEmulator {
             buffer,
             cursor_x: 0,
             cursor_y: 0,
-            current_fg: Color::GREEN,
-            current_bg: Color::BLACK,
-            default_fg: Color::GREEN,
-            default_bg: Color::BLACK,
+            current_fg: fg,
+            current_bg: bg,
+            default_fg: fg,
+            default_bg: bg,
             scrollback: Vec::new(),
             max_scrollback: 1000,
+            utf8: i18n::utf8::Decoder::new(),
             esc_state: EscapeState::Normal,
             esc_params: [0; 16],
             esc_param_idx: 0,
@@ -272,18 +304,19 @@ impl TerminalEmulator {
                         }
 
                         // Print prompt
-                        for &b in b"root@veridian:/# " {
-                            self.process_output_byte(b);
-                        }
+                        self.write_str("root@veridian:/# ");
                     }
                     '\x08' | '\x7f' => {
-                        // Backspace
-                        if !self.line_buffer.is_empty() {
-                            self.line_buffer.pop();
-                            // Erase character on screen: BS + space + BS
-                            self.process_output_byte(b'\x08');
-                            self.process_output_byte(b' ');
-                            self.process_output_byte(b'\x08');
+                        // Backspace removes a whole grapheme cluster
+                        let len = self.line_buffer.len();
+                        let start = i18n::grapheme::prev_boundary(&self.line_buffer, len);
+                        let cells = cjk::string_width(&self.line_buffer[start..]);
+                        self.line_buffer.truncate(start);
+                        // Erase it on screen: BS + space + BS per cell
+                        for b in *b"\x08 \x08" {
+                            for _ in 0..cells {
+                                self.process_output_byte(b);
+                            }
                         }
                     }
                     '\x00' => {
@@ -291,10 +324,10 @@ impl TerminalEmulator {
                         // Handle arrow keys etc. if needed
                         let _ = scancode;
                     }
-                    c if c >= ' ' => {
+                    c if !c.is_control() => {
                         // Printable character: echo + accumulate
                         self.line_buffer.push(c);
-                        self.process_output_byte(c as u8);
+                        self.write_str(c.encode_utf8(&mut [0; 4]));
                     }
                     _ => {}
                 }
@@ -319,45 +352,38 @@ impl TerminalEmulator {
                 if line.starts_with("[SHELL-EXEC]") {
                     continue;
                 }
-                for &b in line.as_bytes() {
-                    self.process_output_byte(b);
-                }
-                self.process_output_byte(b'\r');
-                self.process_output_byte(b'\n');
+                self.write_line(line);
             }
 
             // Display error/not-found messages from the result itself
             match result {
                 crate::services::shell::CommandResult::Success(_) => {}
                 crate::services::shell::CommandResult::Error(msg) => {
-                    for &b in msg.as_bytes() {
-                        if b == b'\n' {
-                            self.process_output_byte(b'\r');
-                        }
-                        self.process_output_byte(b);
-                    }
-                    self.process_output_byte(b'\r');
-                    self.process_output_byte(b'\n');
+                    self.write_line(&msg);
                 }
                 crate::services::shell::CommandResult::NotFound => {
-                    let msg = format!(
-                        "{}: command not found",
-                        cmd.split_whitespace().next().unwrap_or(cmd)
-                    );
-                    for &b in msg.as_bytes() {
-                        self.process_output_byte(b);
-                    }
-                    self.process_output_byte(b'\r');
-                    self.process_output_byte(b'\n');
+                    let name = cmd.split_whitespace().next().unwrap_or(cmd);
+                    self.write_line(&tr_fmt("{}: command not found", &[&name]));
                 }
                 crate::services::shell::CommandResult::Exit(_) => {}
             }
         } else {
-            for &b in b"shell not initialized" {
-                self.process_output_byte(b);
-            }
-            self.process_output_byte(b'\r');
-            self.process_output_byte(b'\n');
+            self.write_line(&tr("shell not initialized"));
+        }
+    }
+
+    /// Write `text` to the screen.
+    fn write_str(&mut self, text: &str) {
+        for &b in text.as_bytes() {
+            self.process_output_byte(b);
+        }
+    }
+
+    /// Write `text` and a line break, turning each `\n` into CR LF.
+    fn write_line(&mut self, text: &str) {
+        for line in text.split('\n') {
+            self.write_str(line);
+            self.write_str("\r\n");
         }
     }
 
@@ -388,66 +414,94 @@ impl TerminalEmulator {
     /// Process a single output byte with ANSI escape sequence support.
     fn process_output_byte(&mut self, byte: u8) {
         match self.esc_state {
-            EscapeState::Normal => self.process_normal(byte),
+            EscapeState::Normal => {
+                for ch in self.utf8.push(byte) {
+                    self.process_normal(ch);
+                }
+            }
             EscapeState::Escape => self.process_escape(byte),
             EscapeState::Csi => self.process_csi(byte),
         }
     }
 
-    /// Handle a byte in normal (non-escape) mode.
-    fn process_normal(&mut self, byte: u8) {
-        match byte {
-            b'\n' => {
-                self.cursor_x = 0;
-                self.cursor_y += 1;
-                if self.cursor_y >= TERMINAL_ROWS {
-                    self.scroll_up();
-                }
-            }
-            b'\r' => {
+    /// Handle a character in normal (non-escape) mode.
+    fn process_normal(&mut self, ch: char) {
+        match ch {
+            '\n' => self.line_feed(),
+            '\r' => {
                 self.cursor_x = 0;
             }
-            b'\t' => {
+            '\t' => {
                 self.cursor_x = (self.cursor_x + 8) & !7;
                 if self.cursor_x >= TERMINAL_COLS {
-                    self.cursor_x = 0;
-                    self.cursor_y += 1;
-                    if self.cursor_y >= TERMINAL_ROWS {
-                        self.scroll_up();
-                    }
+                    self.line_feed();
                 }
             }
-            b'\x08' => {
+            '\x08' => {
                 if self.cursor_x > 0 {
                     self.cursor_x -= 1;
                     self.buffer[self.cursor_y][self.cursor_x] = Cell::default();
                 }
             }
-            0x1B => {
+            '\x1B' => {
                 // ESC — start escape sequence
                 self.esc_state = EscapeState::Escape;
                 self.esc_param_idx = 0;
                 self.esc_params = [0; 16];
             }
-            0x20..=0x7E => {
-                self.buffer[self.cursor_y][self.cursor_x] = Cell {
-                    character: byte as char,
-                    foreground: self.current_fg,
-                    background: self.current_bg,
-                };
-                self.cursor_x += 1;
-                if self.cursor_x >= TERMINAL_COLS {
-                    self.cursor_x = 0;
-                    self.cursor_y += 1;
-                    if self.cursor_y >= TERMINAL_ROWS {
-                        self.scroll_up();
-                    }
-                }
-            }
+            c if !c.is_control() => self.put_char(c),
             _ => {}
         }
     }
 
+    /// Move to the start of the next line, scrolling at the bottom.
+    fn line_feed(&mut self) {
+        self.cursor_x = 0;
+        self.cursor_y += 1;
+        if self.cursor_y >= TERMINAL_ROWS {
+            self.scroll_up();
+        }
+    }
+
+    /// Store a printable character at the cursor and advance by its width.
+    /// Combining marks are dropped: the 8x16 font cannot stack them.
+    fn put_char(&mut self, ch: char) {
+        let width = cjk::char_width(ch) as usize;
+        if width == 0 {
+            return;
+        }
+        // A wide character does not split across lines
+        if self.cursor_x + width > TERMINAL_COLS {
+            self.line_feed();
+        }
+        let cell = Cell {
+            character: ch,
+            foreground: self.current_fg,
+            background: self.current_bg,
+        };
+        let (x, row) = (self.cursor_x, &mut self.buffer[self.cursor_y]);
+        // Overwriting half of a wide character blanks the other half
+        if row[x].character == WIDE_TAIL && x > 0 {
+            row[x - 1] = Cell::default();
+        }
+        if let Some(next) = row.get_mut(x + width) {
+            if next.character == WIDE_TAIL {
+                *next = Cell::default();
+            }
+        }
+        row[x] = cell;
+        if width == 2 {
+            row[x + 1] = Cell {
+                character: WIDE_TAIL,
+                ..cell
+            };
+        }
+        self.cursor_x += width;
+        if self.cursor_x >= TERMINAL_COLS {
+            self.line_feed();
+        }
+    }
+
     /// Handle a byte after ESC was received.
     fn process_escape(&mut self, byte: u8) {
         if byte == b'[' {
@@ -618,17 +672,15 @@ impl TerminalEmulator {
     ///
     /// `buf` is width*height*4 bytes in BGRA format.
     pub fn render(&self, buf: &mut [u8], width: usize, _height: usize) -> Result<(), KernelError> {
-        use super::renderer::draw_char_into_buffer;
+        use super::renderer::draw_glyph_into_buffer;
 
         let char_w = 8;
         let char_h = 16;
 
-        // Clear to black background (BGRA)
+        // Clear to the default background (BGRA)
+        let bg = self.default_bg;
         for chunk in buf.chunks_exact_mut(4) {
-            chunk[0] = 0x00; // B
-            chunk[1] = 0x00; // G
-            chunk[2] = 0x00; // R
-            chunk[3] = 0xFF; // A
+            chunk.copy_from_slice(&[bg.b, bg.g, bg.r, 0xFF]);
         }
 
         // Render each cell with its foreground color
@@ -638,8 +690,8 @@ impl TerminalEmulator {
                 if cell.character == ' ' {
                     continue;
                 }
-                // Background fill for non-black cells
-                if cell.background.r != 0 || cell.background.g != 0 || cell.background.b != 0 {
+                // Background fill for cells with a non-default background
+                if cell.background != self.default_bg {
                     let px0 = x * char_w;
                     let py0 = y * char_h;
                     for dy in 0..char_h {
@@ -657,12 +709,14 @@ impl TerminalEmulator {
                 let fg_color = ((cell.foreground.r as u32) << 16)
                     | ((cell.foreground.g as u32) << 8)
                     | (cell.foreground.b as u32);
-                let ch = cell.character as u8;
-                let cw = crate::desktop::desktop_ext::cjk::char_width(cell.character);
-                if cw == 0 {
-                    continue; // skip zero-width / combining marks
-                }
-                draw_char_into_buffer(buf, width, ch, x * char_w, y * char_h, fg_color);
+                draw_glyph_into_buffer(
+                    buf,
+                    width,
+                    cell.character,
+                    x * char_w,
+                    y * char_h,
+                    fg_color,
+                );
             }
         }
 
@@ -673,11 +727,11 @@ impl TerminalEmulator {
             for dx in 0..char_w {
                 let offset = ((cy + dy) * width + (cx + dx)) * 4;
                 if offset + 3 < buf.len() {
-                    // Invert: use foreground color with some transparency effect
-                    buf[offset] = 0xCC; // B
-                    buf[offset + 1] = 0xCC; // G
-                    buf[offset + 2] = 0xCC; // R
-                    buf[offset + 3] = 0xFF; // A
+                    // Block in the default foreground color
+                    buf[offset] = self.default_fg.b;
+                    buf[offset + 1] = self.default_fg.g;
+                    buf[offset + 2] = self.default_fg.r;
+                    buf[offset + 3] = 0xFF;
                 }
             }
         }
@@ -685,6 +739,35 @@ impl TerminalEmulator {
         Ok(())
     }
 
+    /// Switch the default colors to a new theme. Text in the old default
+    /// colors is recolored; colors set with SGR escapes are kept.
+    pub fn apply_theme(&mut self, colors: &ThemeColors) {
+        let (old_fg, old_bg) = (self.default_fg, self.default_bg);
+        let fg = Color::from(colors.window_foreground);
+        let bg = Color::from(colors.window_background);
+        for cell in self
+            .buffer
+            .iter_mut()
+            .chain(self.scrollback.iter_mut())
+            .flatten()
+        {
+            if cell.foreground == old_fg {
+                cell.foreground = fg;
+            }
+            if cell.background == old_bg {
+                cell.background = bg;
+            }
+        }
+        if self.current_fg == old_fg {
+            self.current_fg = fg;
+        }
+        if self.current_bg == old_bg {
+            self.current_bg = bg;
+        }
+        self.default_fg = fg;
+        self.default_bg = bg;
+    }
+
     /// Get window ID
     pub fn window_id(&self) -> WindowId {
         self.window_id
@@ -760,9 +843,17 @@ impl TerminalManager {
     pub fn write_welcome(&self, terminal_id: usize) {
         let mut terminals = self.terminals.write();
         if let Some(terminal) = terminals.get_mut(terminal_id) {
-            for &b in b"VeridianOS Terminal\r\n\r\nPress ESC to exit GUI.\r\nroot@veridian:/# " {
-                terminal.process_output_byte(b);
-            }
+            terminal.write_line(&tr("VeridianOS Terminal"));
+            terminal.write_line("");
+            terminal.write_line(&tr("Press ESC to exit GUI."));
+            terminal.write_str("root@veridian:/# ");
+        }
+    }
+
+    /// Re-theme every terminal.
+    pub fn apply_theme(&self, colors: &ThemeColors) {
+        for terminal in self.terminals.write().iter_mut() {
+            terminal.apply_theme(colors);
         }
     }
 
@@ -813,6 +904,101 @@ mod tests {
         assert_eq!(cell.character, ' ');
     }
 
+    #[test]
+    fn test_color_from_theme() {
+        let color = Color::from(ThemeColor::from_rgb(0x2E, 0x34, 0x40));
+        assert_eq!(
+            color,
+            Color {
+                r: 0x2E,
+                g: 0x34,
+                b: 0x40
+            }
+        );
+    }
+
+    /// A terminal with no window or PTY behind it.
+    fn headless() -> TerminalEmulator {
+        TerminalEmulator {
+            window_id: 0,
+            surface_id: 0,
+            pool_id: 0,
+            pool_buf_id: 0,
+            pty_master_id: 0,
+            pty_slave_id: 0,
+            buffer: vec![vec![Cell::default(); TERMINAL_COLS]; TERMINAL_ROWS],
+            cursor_x: 0,
+            cursor_y: 0,
+            current_fg: Color::WHITE,
+            current_bg: Color::BLACK,
+            default_fg: Color::WHITE,
+            default_bg: Color::BLACK,
+            scrollback: Vec::new(),
+            max_scrollback: 10,
+            utf8: i18n::utf8::Decoder::new(),
+            esc_state: EscapeState::Normal,
+            esc_params: [0; 16],
+            esc_param_idx: 0,
+            line_buffer: String::new(),
+        }
+    }
+
+    fn row_text(term: &TerminalEmulator, y: usize) -> String {
+        term.buffer[y]
+            .iter()
+            .map(|cell| cell.character)
+            .collect::<String>()
+            .trim_end()
+            .into()
+    }
+
+    #[test]
+    fn test_utf8_output() {
+        let mut term = headless();
+        // "é" split across writes, a bad byte, and a wide character
+        for &b in b"caf\xC3" {
+            term.process_output_byte(b);
+        }
+        for &b in b"\xC2\xA9\xFF\x1B[1m\xE4\xB8\xAD!" {
+            term.process_output_byte(b);
+        }
+        term.process_output_byte(0xFF);
+        assert_eq!(row_text(&term, 0), "caf\u{FFFD}©\u{FFFD}中\0!\u{FFFD}");
+        assert_eq!(term.cursor_x, 10);
+
+        // A combining mark takes no cell
+        term.process_output_byte(b'\r');
+        for &b in "e\u{301}".as_bytes() {
+            term.process_output_byte(b);
+        }
+        assert_eq!(term.cursor_x, 1);
+    }
+
+    #[test]
+    fn test_wide_char_wraps_and_backspace() {
+        let mut term = headless();
+        term.cursor_x = TERMINAL_COLS - 1;
+        term.put_char('中');
+        assert_eq!(term.cursor_y, 1);
+        assert_eq!(term.cursor_x, 2);
+        assert_eq!(term.buffer[1][1].character, WIDE_TAIL);
+
+        // Overwriting the right half blanks the left
+        term.cursor_x = 1;
+        term.put_char('x');
+        assert_eq!(row_text(&term, 1), " x");
+
+        // Backspace erases a whole cluster, two cells for a wide one
+        term.line_buffer = String::from("a中");
+        term.cursor_x = 3;
+        let _ = term.process_input(InputEvent::KeyPress {
+            character: '\x08',
+            scancode: 0,
+        });
+        assert_eq!(term.line_buffer, "a");
+        assert_eq!(term.cursor_x, 1);
+    }
+
     #[test]
     fn test_terminal_dimensions() {
         assert_eq!(TERMINAL_COLS, 80);
@@ -0,0 +1,496 @@
+//! Text storage for the text editor.
+//!
+//! [`GapBuffer`] keeps the document as UTF-8 bytes in one allocation with a
+//! movable gap at the edit point, so typing is amortised O(1) and a
+//! multi-megabyte file costs roughly its own size (the old `Vec<Vec<char>>`
+//! representation cost four bytes per character plus one allocation per
+//! line). [`TextBuffer`] adds a line-start index on top for line/column
+//! addressing.
+//!
+//! Also provides the binary-content heuristic and hex-dump formatting used
+//! by the editor's read-only hex view.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use i18n::grapheme;
+
+/// Minimum gap size allocated when the gap runs out.
+const MIN_GAP: usize = 4096;
+
+/// Bytes examined around the cursor when looking for a cluster boundary.
+/// Longer clusters (long ZWJ sequences) are split at the window edge.
+const CLUSTER_WINDOW: usize = 128;
+
+/// Byte buffer with a movable insertion gap.
+#[derive(Debug, Clone)]
+pub struct GapBuffer {
+    data: Vec<u8>,
+    gap_start: usize,
+    gap_end: usize,
+}
+
+impl Default for GapBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GapBuffer {
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            gap_start: 0,
+            gap_end: 0,
+        }
+    }
+
+    /// Number of bytes stored (excluding the gap).
+    pub fn len(&self) -> usize {
+        self.data.len() - (self.gap_end - self.gap_start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Move the gap so it starts at `pos`.
+    fn move_gap(&mut self, pos: usize) {
+        if pos < self.gap_start {
+            let count = self.gap_start - pos;
+            self.data
+                .copy_within(pos..self.gap_start, self.gap_end - count);
+            self.gap_start = pos;
+            self.gap_end -= count;
+        } else if pos > self.gap_start {
+            let count = pos - self.gap_start;
+            self.data
+                .copy_within(self.gap_end..self.gap_end + count, self.gap_start);
+            self.gap_start += count;
+            self.gap_end += count;
+        }
+    }
+
+    /// Make the gap at least `needed` bytes long.
+    fn reserve(&mut self, needed: usize) {
+        let gap = self.gap_end - self.gap_start;
+        if gap >= needed {
+            return;
+        }
+        // Grow geometrically so streaming appends stay linear overall
+        let grow = (needed - gap).max(self.data.len()).max(MIN_GAP);
+        let tail = self.data.len() - self.gap_end;
+        self.data.resize(self.data.len() + grow, 0);
+        let new_end = self.gap_end + grow;
+        self.data
+            .copy_within(self.gap_end..self.gap_end + tail, new_end);
+        self.gap_end = new_end;
+    }
+
+    /// Insert `bytes` at `pos` (clamped to the end).
+    pub fn insert(&mut self, pos: usize, bytes: &[u8]) {
+        let pos = pos.min(self.len());
+        self.reserve(bytes.len());
+        self.move_gap(pos);
+        self.data[self.gap_start..self.gap_start + bytes.len()].copy_from_slice(bytes);
+        self.gap_start += bytes.len();
+    }
+
+    /// Remove up to `len` bytes starting at `pos`.
+    pub fn remove(&mut self, pos: usize, len: usize) {
+        let pos = pos.min(self.len());
+        let len = len.min(self.len() - pos);
+        self.move_gap(pos);
+        self.gap_end += len;
+    }
+
+    /// Byte at `pos`, if in range.
+    pub fn get(&self, pos: usize) -> Option<u8> {
+        if pos < self.gap_start {
+            Some(self.data[pos])
+        } else {
+            self.data
+                .get(pos + (self.gap_end - self.gap_start))
+                .copied()
+        }
+    }
+
+    /// The contents as the two slices on either side of the gap.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        (&self.data[..self.gap_start], &self.data[self.gap_end..])
+    }
+
+    /// Copy bytes `start..end` out of the buffer.
+    pub fn copy_range(&self, start: usize, end: usize) -> Vec<u8> {
+        let end = end.min(self.len());
+        let start = start.min(end);
+        let (front, back) = self.as_slices();
+        let mut out = Vec::with_capacity(end - start);
+        if start < front.len() {
+            out.extend_from_slice(&front[start..end.min(front.len())]);
+        }
+        if end > front.len() {
+            out.extend_from_slice(&back[start.saturating_sub(front.len())..end - front.len()]);
+        }
+        out
+    }
+}
+
+/// A [`GapBuffer`] with a line index. Columns are byte offsets within a
+/// line; use [`TextBuffer::prev_boundary`]/[`TextBuffer::next_boundary`]
+/// to step over whole UTF-8 characters.
+#[derive(Debug, Clone)]
+pub struct TextBuffer {
+    text: GapBuffer,
+    /// Byte offset of the start of each line; always starts with 0
+    line_starts: Vec<usize>,
+}
+
+impl Default for TextBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        Self {
+            text: GapBuffer::new(),
+            line_starts: vec![0],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Total size in bytes.
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Length of `line` in bytes, excluding its newline.
+    pub fn line_len(&self, line: usize) -> usize {
+        let Some(&start) = self.line_starts.get(line) else {
+            return 0;
+        };
+        match self.line_starts.get(line + 1) {
+            Some(&next) => next - start - 1,
+            None => self.len() - start,
+        }
+    }
+
+    /// Bytes of `line`, excluding its newline.
+    pub fn line(&self, line: usize) -> Vec<u8> {
+        let Some(&start) = self.line_starts.get(line) else {
+            return Vec::new();
+        };
+        self.text.copy_range(start, start + self.line_len(line))
+    }
+
+    /// At most the first `max_len` bytes of `line` (for drawing very long
+    /// lines without copying all of them).
+    pub fn line_head(&self, line: usize, max_len: usize) -> Vec<u8> {
+        let Some(&start) = self.line_starts.get(line) else {
+            return Vec::new();
+        };
+        self.text
+            .copy_range(start, start + self.line_len(line).min(max_len))
+    }
+
+    /// Byte offset of (`line`, `col`), clamped to the document.
+    pub fn offset(&self, line: usize, col: usize) -> usize {
+        let line = line.min(self.line_count() - 1);
+        self.line_starts[line] + col.min(self.line_len(line))
+    }
+
+    /// Line containing byte `offset`.
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&s| s <= offset) - 1
+    }
+
+    /// Insert `bytes` at byte `offset`, which may contain newlines.
+    pub fn insert(&mut self, offset: usize, bytes: &[u8]) {
+        let offset = offset.min(self.len());
+        self.text.insert(offset, bytes);
+
+        let line = self.line_of(offset);
+        for start in &mut self.line_starts[line + 1..] {
+            *start += bytes.len();
+        }
+        let new_starts = bytes
+            .iter()
+            .enumerate()
+            .filter(|(_, &b)| b == b'\n')
+            .map(|(i, _)| offset + i + 1);
+        self.line_starts.splice(line + 1..line + 1, new_starts);
+    }
+
+    /// Append a chunk at the end (streaming load).
+    pub fn append(&mut self, bytes: &[u8]) {
+        self.insert(self.len(), bytes);
+    }
+
+    /// Remove `len` bytes at byte `offset`, joining lines as needed.
+    pub fn remove(&mut self, offset: usize, len: usize) {
+        let offset = offset.min(self.len());
+        let end = (offset + len).min(self.len());
+        self.text.remove(offset, end - offset);
+
+        self.line_starts.retain(|&s| s <= offset || s > end);
+        for start in self.line_starts.iter_mut().filter(|s| **s > end) {
+            *start -= end - offset;
+        }
+    }
+
+    /// Column of the grapheme cluster boundary before `col` on `line`, so
+    /// the cursor steps over `e` plus a combining accent or a flag in one
+    /// move.
+    pub fn prev_boundary(&self, line: usize, col: usize) -> usize {
+        let start = self.line_starts[line];
+        let col = col.min(self.line_len(line));
+        let mut from = col.saturating_sub(CLUSTER_WINDOW);
+        while from < col && is_continuation(self.text.get(start + from).unwrap_or(0)) {
+            from += 1;
+        }
+        let window = self.text.copy_range(start + from, start + col);
+        match core::str::from_utf8(&window) {
+            Ok(text) if !text.is_empty() => from + grapheme::prev_boundary(text, text.len()),
+            _ => self.prev_char(line, col),
+        }
+    }
+
+    /// `col` moved back to the start of the cluster it falls inside.
+    pub fn char_start(&self, line: usize, col: usize) -> usize {
+        let col = col.min(self.line_len(line));
+        if col == self.line_len(line) {
+            return col;
+        }
+        let char_start = self.prev_char(line, col + 1);
+        self.prev_boundary(line, self.next_char(line, char_start))
+    }
+
+    /// Column of the grapheme cluster boundary after `col` on `line`.
+    pub fn next_boundary(&self, line: usize, col: usize) -> usize {
+        let start = self.line_starts[line];
+        let len = self.line_len(line);
+        let col = col.min(len);
+        let window = self
+            .text
+            .copy_range(start + col, start + (col + CLUSTER_WINDOW).min(len));
+        // The window may end inside a character; decode what is whole
+        let valid = match core::str::from_utf8(&window) {
+            Ok(text) => text,
+            Err(e) => core::str::from_utf8(&window[..e.valid_up_to()]).unwrap_or(""),
+        };
+        if valid.is_empty() {
+            return self.next_char(line, col);
+        }
+        col + grapheme::next_boundary(valid, 0)
+    }
+
+    /// Column of the character boundary before `col`, for text that is not
+    /// valid UTF-8.
+    fn prev_char(&self, line: usize, col: usize) -> usize {
+        let start = self.line_starts[line];
+        let mut col = col.min(self.line_len(line));
+        while col > 0 {
+            col -= 1;
+            if !is_continuation(self.text.get(start + col).unwrap_or(0)) {
+                break;
+            }
+        }
+        col
+    }
+
+    /// Column of the character boundary after `col`.
+    fn next_char(&self, line: usize, col: usize) -> usize {
+        let start = self.line_starts[line];
+        let len = self.line_len(line);
+        let mut col = col.min(len);
+        if col < len {
+            col += 1;
+            while col < len && is_continuation(self.text.get(start + col).unwrap_or(0)) {
+                col += 1;
+            }
+        }
+        col
+    }
+
+    /// The contents as two contiguous slices (for saving).
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        self.text.as_slices()
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Heuristic: does `sample` look like binary rather than text?
+///
+/// True if it contains a NUL byte, is not valid UTF-8, or more than 1 in 10
+/// bytes are control characters other than tab, newline, carriage return,
+/// form feed and escape. `sample` may be any window of a larger file: up to
+/// three leading continuation bytes and a truncated trailing sequence are
+/// tolerated.
+pub fn looks_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+
+    let skip = sample
+        .iter()
+        .take(3)
+        .take_while(|&&b| is_continuation(b))
+        .count();
+    if let Err(e) = core::str::from_utf8(&sample[skip..]) {
+        if e.error_len().is_some() {
+            return true;
+        }
+    }
+
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    control * 10 > sample.len()
+}
+
+/// Bytes shown per hex-view row.
+pub const HEX_ROW_BYTES: usize = 16;
+
+/// Format one hex-dump row: offset, up to 16 hex bytes and an ASCII column.
+pub fn hex_row(offset: u64, bytes: &[u8]) -> String {
+    let mut row = String::with_capacity(80);
+    let _ = write!(row, "{:08x}  ", offset);
+    for i in 0..HEX_ROW_BYTES {
+        match bytes.get(i) {
+            Some(b) => {
+                let _ = write!(row, "{:02x} ", b);
+            }
+            None => row.push_str("   "),
+        }
+        if i == 7 {
+            row.push(' ');
+        }
+    }
+    row.push('|');
+    for &b in bytes.iter().take(HEX_ROW_BYTES) {
+        row.push(if (0x20..=0x7E).contains(&b) {
+            b as char
+        } else {
+            '.'
+        });
+    }
+    row.push('|');
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(buf: &TextBuffer) -> Vec<u8> {
+        let (a, b) = buf.as_slices();
+        [a, b].concat()
+    }
+
+    #[test]
+    fn test_gap_buffer_edits() {
+        let mut gap = GapBuffer::new();
+        gap.insert(0, b"hello world");
+        gap.insert(5, b",");
+        gap.remove(0, 1);
+        gap.insert(0, b"J");
+        assert_eq!(gap.copy_range(0, gap.len()), b"Jello, world");
+        assert_eq!(gap.copy_range(3, 8), b"lo, w");
+        assert_eq!(gap.get(11), Some(b'd'));
+        assert_eq!(gap.get(12), None);
+    }
+
+    #[test]
+    fn test_line_index() {
+        let mut buf = TextBuffer::new();
+        buf.append(b"one\ntw");
+        buf.append(b"o\nthree");
+        assert_eq!(buf.line_count(), 3);
+        assert_eq!(buf.line(1), b"two");
+        assert_eq!(buf.line_len(2), 5);
+
+        // Split line 0 and re-join it
+        let at = buf.offset(0, 2);
+        buf.insert(at, b"\n");
+        assert_eq!(buf.line_count(), 4);
+        assert_eq!(buf.line(0), b"on");
+        assert_eq!(buf.line(1), b"e");
+        buf.remove(at, 1);
+        assert_eq!(buf.line_count(), 3);
+        assert_eq!(contents(&buf), b"one\ntwo\nthree");
+
+        // Removing across lines drops their starts
+        buf.remove(buf.offset(0, 1), 6);
+        assert_eq!(contents(&buf), b"o\nthree");
+        assert_eq!(buf.line_count(), 2);
+        assert_eq!(buf.line(1), b"three");
+    }
+
+    #[test]
+    fn test_utf8_boundaries() {
+        let mut buf = TextBuffer::new();
+        buf.append("aé€b".as_bytes());
+        assert_eq!(buf.next_boundary(0, 1), 3);
+        assert_eq!(buf.next_boundary(0, 3), 6);
+        assert_eq!(buf.prev_boundary(0, 6), 3);
+        assert_eq!(buf.prev_boundary(0, 3), 1);
+        assert_eq!(buf.prev_boundary(0, 0), 0);
+        assert_eq!(buf.char_start(0, 4), 3);
+        assert_eq!(buf.char_start(0, 6), 6);
+        assert_eq!(buf.char_start(0, 9), 7);
+        assert_eq!(buf.line_head(0, 3), "aé".as_bytes());
+    }
+
+    #[test]
+    fn test_grapheme_boundaries() {
+        let mut buf = TextBuffer::new();
+        buf.append("xe\u{301}🇩🇪y\n".as_bytes());
+        buf.append(b"\xC3\xA9\xFFz");
+        // `e` + combining acute is one step, the flag another
+        assert_eq!(buf.next_boundary(0, 1), 4);
+        assert_eq!(buf.next_boundary(0, 4), 12);
+        assert_eq!(buf.prev_boundary(0, 12), 4);
+        assert_eq!(buf.prev_boundary(0, 4), 1);
+        assert_eq!(buf.char_start(0, 3), 1);
+        assert_eq!(buf.char_start(0, 8), 4);
+        // Invalid UTF-8 falls back to stepping by character
+        assert_eq!(buf.prev_boundary(1, 3), 2);
+        assert_eq!(buf.next_boundary(1, 0), 2);
+        assert_eq!(buf.next_boundary(1, 2), 3);
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(!looks_binary(b"fn main() {\n\tprintln!();\n}\n"));
+        assert!(!looks_binary("caf\u{e9}".as_bytes()));
+        assert!(looks_binary(b"\x7fELF\x02\x01\x01\x00"));
+        assert!(looks_binary(&[0xFF, 0xFE, b'a', b'b']));
+        // Window cut in the middle of multi-byte characters
+        let text = "é€".as_bytes();
+        assert!(!looks_binary(&text[1..4]));
+    }
+
+    #[test]
+    fn test_hex_row() {
+        let row = hex_row(0x10, b"AB\x00");
+        assert!(row.starts_with("00000010  41 42 00 "));
+        assert!(row.ends_with("|AB.|"));
+    }
+}
@@ -1,23 +1,54 @@
 //! GUI Text Editor Application
 //!
 //! Simple text editor with basic editing capabilities.
+//!
+//! Text lives in a [`TextBuffer`] (gap buffer plus line index) and is loaded
+//! in chunks, so multi-megabyte files neither need a second full copy nor
+//! have to be valid UTF-8 as a whole. Binary files and files too large to
+//! edit open in a read-only hex view that reads only the rows on screen.
+//!
+//! Editing behaviour beyond the basics comes from plugins (see
+//! [`super::editor_plugin`]): the bundled auto-brackets and
+//! trailing-whitespace plugins, plus any WebAssembly plugins installed in
+//! [`PLUGIN_DIR`].
 
 // Phase 6 (desktop) -- editor fields and methods are defined but
 // rendering is not yet connected to the compositor.
 
-use alloc::{format, string::String, vec, vec::Vec};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
 
 use spin::RwLock;
 
+use super::{
+    desktop_ext::{cjk, theme::ThemeColors},
+    editor_plugin::{
+        bundled::{AutoBrackets, TrimWhitespace},
+        wasm::scan_dir,
+        EditorEvent, EditorPlugin, EventResult, PluginContext, PluginManager, PLUGIN_DIR,
+    },
+    text_buffer::{hex_row, looks_binary, TextBuffer, HEX_ROW_BYTES},
+};
 use crate::{
     desktop::window_manager::{with_window_manager, InputEvent, WindowId},
     error::KernelError,
     fs::{get_vfs, OpenFlags},
+    services::locale::{tr, tr_fmt},
     sync::once_lock::GlobalState,
 };
 
-/// Text buffer line
-type Line = Vec<char>;
+/// Files are read in chunks of this size.
+const LOAD_CHUNK: usize = 64 * 1024;
+
+/// Files larger than this open in the read-only hex view.
+const MAX_EDIT_SIZE: usize = 32 * 1024 * 1024;
+
+/// Read-only hex view of a file, streamed from disk a screenful at a time.
+struct HexView {
+    /// File size in bytes
+    size: usize,
+    /// Bytes of the rows currently on screen (row `scroll_line` onwards)
+    window: Vec<u8>,
+}
 
 /// Text editor state
 pub struct TextEditor {
@@ -34,14 +65,17 @@ pub struct TextEditor {
     /// File path (None if new file)
     file_path: Option<String>,
 
-    /// Text buffer (lines of characters)
-    buffer: Vec<Line>,
+    /// Document text
+    buffer: TextBuffer,
+
+    /// Hex view, if the file is binary or too large to edit
+    hex_view: Option<HexView>,
 
-    /// Cursor position (line, column)
+    /// Cursor position (line, byte column)
     cursor_line: usize,
     cursor_col: usize,
 
-    /// Scroll offset (top line visible)
+    /// Scroll offset (top line, or top hex row, visible)
     scroll_line: usize,
 
     /// Modified flag
@@ -51,13 +85,17 @@ pub struct TextEditor {
     width: u32,
     height: u32,
 
-    /// Visible rows (used by render to limit line display)
-    #[allow(dead_code)] // Computed for future scroll-window rendering
+    /// Visible rows (keeps the cursor on screen)
     visible_rows: usize,
 
-    /// Visible columns
-    #[allow(dead_code)] // Computed for future horizontal scroll rendering
+    /// Visible columns (bounds how much of a long line is drawn)
     visible_cols: usize,
+
+    /// Loaded plugins
+    plugins: PluginManager,
+
+    /// Last plugin status message, shown in the bottom status bar
+    status_message: Option<String>,
 }
 
 impl TextEditor {
@@ -91,7 +129,8 @@ impl TextEditor {
             pool_id,
             pool_buf_id,
             file_path: file_path.clone(),
-            buffer: vec![Vec::new()], // Start with one empty line
+            buffer: TextBuffer::new(),
+            hex_view: None,
             cursor_line: 0,
             cursor_col: 0,
             scroll_line: 0,
@@ -100,8 +139,12 @@ impl TextEditor {
             height,
             visible_rows,
             visible_cols,
+            plugins: PluginManager::new(),
+            status_message: None,
         };
 
+        editor.load_default_plugins();
+
         // Load file if specified
         if let Some(ref path) = file_path {
             editor.load_file(path)?;
@@ -113,101 +156,196 @@ impl TextEditor {
     }
 
     /// Load file from filesystem
+    ///
+    /// Text is streamed into the buffer [`LOAD_CHUNK`] bytes at a time.
+    /// Binary files and files over [`MAX_EDIT_SIZE`] open in the read-only
+    /// hex view instead.
     pub fn load_file(&mut self, path: &str) -> Result<(), KernelError> {
         println!("[TEXT-EDITOR] Loading file: {}", path);
 
-        let vfs = get_vfs();
-
-        // Open file
-        match vfs.read().open(path, OpenFlags::read_only()) {
-            Ok(node) => {
-                // Read file
-                let metadata = node.metadata().map_err(|_| KernelError::InvalidArgument {
-                    name: "file_metadata",
-                    value: "failed_to_read",
-                })?;
-                let mut file_buffer = vec![0u8; metadata.size];
-
-                match node.read(0, &mut file_buffer) {
-                    Ok(_bytes_read) => {
-                        // Parse content into lines
-                        self.buffer.clear();
-                        let content = core::str::from_utf8(&file_buffer).map_err(|_| {
-                            KernelError::InvalidArgument {
-                                name: "file_content",
-                                value: "invalid_utf8",
-                            }
-                        })?;
-
-                        for line in content.lines() {
-                            self.buffer.push(line.chars().collect());
-                        }
-
-                        if self.buffer.is_empty() {
-                            self.buffer.push(Vec::new());
-                        }
-
-                        self.modified = false;
-                        println!("[TEXT-EDITOR] Loaded {} lines", self.buffer.len());
-                    }
-                    Err(_e) => {
-                        println!("[TEXT-EDITOR] Failed to read file");
-                        return Err(KernelError::InvalidArgument {
-                            name: "file_read",
-                            value: "failed",
-                        });
-                    }
-                }
-            }
-            Err(_e) => {
+        let node = get_vfs()
+            .read()
+            .open(path, OpenFlags::read_only())
+            .map_err(|_| {
                 println!("[TEXT-EDITOR] Failed to open file");
-                return Err(KernelError::InvalidArgument {
+                KernelError::InvalidArgument {
                     name: "file_open",
                     value: "failed",
-                });
+                }
+            })?;
+        let size = node
+            .metadata()
+            .map_err(|_| KernelError::InvalidArgument {
+                name: "file_metadata",
+                value: "failed_to_read",
+            })?
+            .size;
+
+        self.file_path = Some(String::from(path));
+        self.buffer.clear();
+        self.hex_view = None;
+        self.cursor_line = 0;
+        self.cursor_col = 0;
+        self.scroll_line = 0;
+        self.modified = false;
+
+        let mut binary = size > MAX_EDIT_SIZE;
+        let mut chunk = vec![0u8; LOAD_CHUNK.min(size.max(1))];
+        let mut offset = 0;
+        while !binary && offset < size {
+            let n = node.read(offset, &mut chunk).map_err(|_| {
+                println!("[TEXT-EDITOR] Failed to read file");
+                KernelError::InvalidArgument {
+                    name: "file_read",
+                    value: "failed",
+                }
+            })?;
+            if n == 0 {
+                break;
             }
+            if looks_binary(&chunk[..n]) {
+                binary = true;
+                break;
+            }
+            self.buffer.append(&chunk[..n]);
+            offset += n;
+        }
+
+        if binary {
+            self.buffer.clear();
+            self.hex_view = Some(HexView {
+                size,
+                window: Vec::new(),
+            });
+            self.refresh_hex_window();
+            println!("[TEXT-EDITOR] Opened {} bytes in read-only hex view", size);
+        } else {
+            println!("[TEXT-EDITOR] Loaded {} lines", self.buffer.line_count());
+            self.run_plugins(|plugins, ctx| plugins.dispatch(EditorEvent::Opened, ctx));
         }
 
         Ok(())
     }
 
+    /// Load the bundled plugins and any WebAssembly plugins in
+    /// [`PLUGIN_DIR`].
+    fn load_default_plugins(&mut self) {
+        let mut plugins: Vec<Box<dyn EditorPlugin>> =
+            vec![Box::new(AutoBrackets), Box::new(TrimWhitespace)];
+        for plugin in scan_dir(PLUGIN_DIR) {
+            plugins.push(Box::new(plugin));
+        }
+        for plugin in plugins {
+            let name = String::from(plugin.name());
+            if let Err(e) = self.load_plugin(plugin) {
+                println!("[TEXT-EDITOR] Plugin {} not loaded: {:?}", name, e);
+            }
+        }
+    }
+
+    /// Load a plugin into this editor.
+    pub fn load_plugin(&mut self, plugin: Box<dyn EditorPlugin>) -> Result<(), KernelError> {
+        self.run_plugins(|plugins, ctx| plugins.load(plugin, ctx))
+    }
+
+    /// Unload plugin `name`.
+    pub fn unload_plugin(&mut self, name: &str) -> Result<(), KernelError> {
+        self.run_plugins(|plugins, ctx| plugins.unload(name, ctx))
+    }
+
+    /// Enable or disable plugin `name`.
+    pub fn set_plugin_enabled(&mut self, name: &str, enabled: bool) -> Result<(), KernelError> {
+        self.plugins.set_enabled(name, enabled)
+    }
+
+    /// Loaded plugins as (name, enabled).
+    pub fn plugins(&self) -> Vec<(String, bool)> {
+        self.plugins.plugins()
+    }
+
+    /// Lend the buffer and cursor to the plugins for `f`, then take back
+    /// whatever they changed.
+    fn run_plugins<R>(&mut self, f: impl FnOnce(&mut PluginManager, &mut PluginContext) -> R) -> R {
+        let mut ctx = PluginContext::new(
+            core::mem::take(&mut self.buffer),
+            self.cursor_line,
+            self.cursor_col,
+        );
+        let result = f(&mut self.plugins, &mut ctx);
+        self.buffer = ctx.buffer;
+        self.cursor_line = ctx.cursor_line;
+        self.cursor_col = ctx.cursor_col;
+        self.modified |= ctx.modified;
+        if ctx.status.is_some() {
+            self.status_message = ctx.status;
+        }
+        result
+    }
+
+    /// Re-read the hex view rows currently on screen.
+    fn refresh_hex_window(&mut self) {
+        let (Some(hex), Some(path)) = (self.hex_view.as_mut(), self.file_path.as_deref()) else {
+            return;
+        };
+        let start = self.scroll_line * HEX_ROW_BYTES;
+        let len = (self.visible_rows * HEX_ROW_BYTES).min(hex.size.saturating_sub(start));
+        hex.window.resize(len, 0);
+        let read = get_vfs()
+            .read()
+            .open(path, OpenFlags::read_only())
+            .and_then(|node| node.read(start, &mut hex.window));
+        hex.window.truncate(read.unwrap_or(0));
+    }
+
+    /// Scroll the hex view by `rows` (negative = up).
+    fn scroll_hex(&mut self, rows: isize) {
+        let Some(ref hex) = self.hex_view else {
+            return;
+        };
+        let total_rows = hex.size.div_ceil(HEX_ROW_BYTES);
+        let max_top = total_rows.saturating_sub(self.visible_rows);
+        self.scroll_line = self.scroll_line.saturating_add_signed(rows).min(max_top);
+        self.refresh_hex_window();
+    }
+
     /// Save file to filesystem
     pub fn save_file(&mut self) -> Result<(), KernelError> {
-        let path = self
-            .file_path
-            .as_ref()
-            .ok_or(KernelError::InvalidArgument {
-                name: "file_path",
-                value: "no_path_specified",
-            })?;
-
-        println!("[TEXT-EDITOR] Saving file: {}", path);
+        let path = self.file_path.clone().ok_or(KernelError::InvalidArgument {
+            name: "file_path",
+            value: "no_path_specified",
+        })?;
 
-        // Convert buffer to bytes
-        let mut content = String::new();
-        for line in &self.buffer {
-            for &ch in line {
-                content.push(ch);
-            }
-            content.push('\n');
+        if self.hex_view.is_some() {
+            return Err(KernelError::InvalidState {
+                expected: "text mode",
+                actual: "read-only hex view",
+            });
         }
 
-        let bytes = content.as_bytes();
+        self.run_plugins(|plugins, ctx| plugins.dispatch(EditorEvent::BeforeSave, ctx));
+
+        println!("[TEXT-EDITOR] Saving file: {}", path);
+
+        let (front, back) = self.buffer.as_slices();
+        let total = front.len() + back.len();
 
         // Write to filesystem
         let vfs = get_vfs();
 
         // First check if file exists, otherwise create it
-        match vfs.read().open(path, OpenFlags::read_only()) {
+        match vfs.read().open(&path, OpenFlags::read_only()) {
             Ok(node) => {
-                // File exists, write to it
-                node.write(0, bytes)
-                    .map_err(|_| KernelError::InvalidArgument {
-                        name: "file_write",
-                        value: "failed",
-                    })?;
+                // File exists: write both halves of the gap buffer, then
+                // drop any tail left over from a longer previous version
+                let write_failed = |_| KernelError::InvalidArgument {
+                    name: "file_write",
+                    value: "failed",
+                };
+                node.write(0, front).map_err(write_failed)?;
+                node.write(front.len(), back).map_err(write_failed)?;
+                node.truncate(total).map_err(write_failed)?;
                 self.modified = false;
-                println!("[TEXT-EDITOR] File saved ({} bytes)", bytes.len());
+                println!("[TEXT-EDITOR] File saved ({} bytes)", total);
                 Ok(())
             }
             Err(_) => {
@@ -229,6 +367,27 @@ impl TextEditor {
             scancode,
         } = event
         {
+            // The hex view is read-only: only scrolling and Ctrl+N apply
+            if self.hex_view.is_some() && character != '\x0E' {
+                let page = self.visible_rows as isize;
+                match scancode {
+                    72 => self.scroll_hex(-1),    // Up arrow
+                    80 => self.scroll_hex(1),     // Down arrow
+                    73 => self.scroll_hex(-page), // Page Up
+                    81 => self.scroll_hex(page),  // Page Down
+                    _ => {}
+                }
+                return Ok(());
+            }
+
+            if character != '\0' {
+                let result = self.run_plugins(|plugins, ctx| plugins.dispatch_key(character, ctx));
+                if result == EventResult::Handled {
+                    self.scroll_to_cursor();
+                    return Ok(());
+                }
+            }
+
             match character {
                 '\n' | '\r' => {
                     // Insert newline
@@ -251,7 +410,7 @@ impl TextEditor {
                 '\x0E' => {
                     // Ctrl+N: New file
                     self.buffer.clear();
-                    self.buffer.push(alloc::vec::Vec::new());
+                    self.hex_view = None;
                     self.cursor_line = 0;
                     self.cursor_col = 0;
                     self.scroll_line = 0;
@@ -279,6 +438,7 @@ impl TextEditor {
                     }
                 }
             }
+            self.scroll_to_cursor();
         }
 
         Ok(())
@@ -286,107 +446,116 @@ impl TextEditor {
 
     /// Insert character at cursor
     fn insert_char(&mut self, ch: char) {
-        if self.cursor_line < self.buffer.len() {
-            self.buffer[self.cursor_line].insert(self.cursor_col, ch);
-            self.cursor_col += 1;
-            self.modified = true;
-        }
+        let mut utf8 = [0u8; 4];
+        let bytes = ch.encode_utf8(&mut utf8).as_bytes();
+        let offset = self.buffer.offset(self.cursor_line, self.cursor_col);
+        self.buffer.insert(offset, bytes);
+        self.cursor_col += bytes.len();
+        self.modified = true;
     }
 
     /// Delete character before cursor
     fn delete_char(&mut self) {
         if self.cursor_col > 0 {
-            self.buffer[self.cursor_line].remove(self.cursor_col - 1);
-            self.cursor_col -= 1;
+            let prev = self.buffer.prev_boundary(self.cursor_line, self.cursor_col);
+            let offset = self.buffer.offset(self.cursor_line, prev);
+            self.buffer.remove(offset, self.cursor_col - prev);
+            self.cursor_col = prev;
             self.modified = true;
         } else if self.cursor_line > 0 {
-            // Join with previous line
-            let current_line = self.buffer.remove(self.cursor_line);
+            // Join with previous line by removing its newline
+            let offset = self.buffer.offset(self.cursor_line, 0);
             self.cursor_line -= 1;
-            self.cursor_col = self.buffer[self.cursor_line].len();
-            self.buffer[self.cursor_line].extend(current_line);
+            self.cursor_col = self.buffer.line_len(self.cursor_line);
+            self.buffer.remove(offset - 1, 1);
             self.modified = true;
         }
     }
 
     /// Insert newline at cursor
     fn insert_newline(&mut self) {
-        if self.cursor_line < self.buffer.len() {
-            let rest = self.buffer[self.cursor_line].split_off(self.cursor_col);
-            self.cursor_line += 1;
-            self.buffer.insert(self.cursor_line, rest);
-            self.cursor_col = 0;
-            self.modified = true;
-        }
+        let offset = self.buffer.offset(self.cursor_line, self.cursor_col);
+        self.buffer.insert(offset, b"\n");
+        self.cursor_line += 1;
+        self.cursor_col = 0;
+        self.modified = true;
     }
 
     /// Move cursor up
     fn move_cursor_up(&mut self) {
         if self.cursor_line > 0 {
             self.cursor_line -= 1;
-            self.cursor_col = self.cursor_col.min(self.buffer[self.cursor_line].len());
+            self.cursor_col = self.buffer.char_start(self.cursor_line, self.cursor_col);
         }
     }
 
     /// Move cursor down
     fn move_cursor_down(&mut self) {
-        if self.cursor_line < self.buffer.len() - 1 {
+        if self.cursor_line < self.buffer.line_count() - 1 {
             self.cursor_line += 1;
-            self.cursor_col = self.cursor_col.min(self.buffer[self.cursor_line].len());
+            self.cursor_col = self.buffer.char_start(self.cursor_line, self.cursor_col);
         }
     }
 
     /// Move cursor left
     fn move_cursor_left(&mut self) {
         if self.cursor_col > 0 {
-            self.cursor_col -= 1;
+            self.cursor_col = self.buffer.prev_boundary(self.cursor_line, self.cursor_col);
         } else if self.cursor_line > 0 {
             self.cursor_line -= 1;
-            self.cursor_col = self.buffer[self.cursor_line].len();
+            self.cursor_col = self.buffer.line_len(self.cursor_line);
         }
     }
 
     /// Move cursor right
     fn move_cursor_right(&mut self) {
-        if self.cursor_col < self.buffer[self.cursor_line].len() {
-            self.cursor_col += 1;
-        } else if self.cursor_line < self.buffer.len() - 1 {
+        if self.cursor_col < self.buffer.line_len(self.cursor_line) {
+            self.cursor_col = self.buffer.next_boundary(self.cursor_line, self.cursor_col);
+        } else if self.cursor_line < self.buffer.line_count() - 1 {
             self.cursor_line += 1;
             self.cursor_col = 0;
         }
     }
 
+    /// Adjust the scroll offset so the cursor line is visible.
+    fn scroll_to_cursor(&mut self) {
+        if self.cursor_line < self.scroll_line {
+            self.scroll_line = self.cursor_line;
+        } else if self.cursor_line >= self.scroll_line + self.visible_rows {
+            self.scroll_line = self.cursor_line + 1 - self.visible_rows;
+        }
+    }
+
     /// Render text editor to a BGRA pixel buffer.
     ///
     /// `buf` is width*height*4 bytes in BGRA format.
     pub fn render(&self, buf: &mut [u8], width: usize, height: usize) -> Result<(), KernelError> {
-        use super::renderer::{draw_char_into_buffer, draw_string_into_buffer};
+        use super::renderer::draw_string_into_buffer;
 
         let char_h = 16;
+        let colors = crate::desktop::config::colors();
 
-        // Clear to very dark gray (BGRA: 0x1E1E1E -- VS Code-like)
+        // Clear to the theme's window background
+        let background = colors.window_background.to_bgra();
         for chunk in buf.chunks_exact_mut(4) {
-            chunk[0] = 0x1E; // B
-            chunk[1] = 0x1E; // G
-            chunk[2] = 0x1E; // R
-            chunk[3] = 0xFF; // A
+            chunk.copy_from_slice(&background);
         }
 
-        // Status bar at top (dark blue-gray background)
+        // Status bar at top
+        let bar = colors.panel_background.to_bgra();
         for x in 0..width {
             for dy in 0..20 {
                 let offset = (dy * width + x) * 4;
                 if offset + 3 < buf.len() {
-                    buf[offset] = 0x40; // B
-                    buf[offset + 1] = 0x30; // G
-                    buf[offset + 2] = 0x25; // R
-                    buf[offset + 3] = 0xFF;
+                    buf[offset..offset + 4].copy_from_slice(&bar);
                 }
             }
         }
 
         // Build status text
-        let status = if let Some(ref path) = self.file_path {
+        let status = if let (Some(hex), Some(path)) = (&self.hex_view, &self.file_path) {
+            tr_fmt("{} [hex, read-only] {} bytes", &[path, &hex.size])
+        } else if let Some(ref path) = self.file_path {
             if self.modified {
                 format!(
                     "{}* L{} C{}",
@@ -404,55 +573,29 @@ impl TextEditor {
             }
         } else {
             format!(
-                "[New File] L{} C{}",
+                "{} L{} C{}",
+                tr("[New File]"),
                 self.cursor_line + 1,
                 self.cursor_col + 1
             )
         };
-        draw_string_into_buffer(buf, width, status.as_bytes(), 6, 2, 0xCCCCCC);
+        draw_string_into_buffer(
+            buf,
+            width,
+            status.as_bytes(),
+            6,
+            2,
+            colors.panel_foreground.rgb(),
+        );
 
         // Render text lines
         let text_y_start = 24;
         let max_visible = (height - text_y_start) / char_h;
 
-        for (i, line) in self
-            .buffer
-            .iter()
-            .enumerate()
-            .skip(self.scroll_line)
-            .take(max_visible)
-        {
-            let row = i - self.scroll_line;
-            let y = text_y_start + row * char_h;
-
-            // Draw line number (dim)
-            let line_num = i + 1;
-            let num_str = format!("{:>4} ", line_num);
-            draw_string_into_buffer(buf, width, num_str.as_bytes(), 0, y, 0x606060);
-
-            // Draw text content
-            let text_x = 5 * 8; // After line number
-            for (j, &ch) in line.iter().enumerate() {
-                if ch as u32 >= 0x20 && (ch as u32) <= 0x7E {
-                    draw_char_into_buffer(buf, width, ch as u8, text_x + j * 8, y, 0xD4D4D4);
-                }
-            }
-
-            // Draw cursor on this line
-            if i == self.cursor_line {
-                let cursor_px = text_x + self.cursor_col * 8;
-                for dy in 0..char_h {
-                    for dx in 0..2 {
-                        let offset = ((y + dy) * width + cursor_px + dx) * 4;
-                        if offset + 3 < buf.len() {
-                            buf[offset] = 0xFF; // B
-                            buf[offset + 1] = 0xFF; // G
-                            buf[offset + 2] = 0xFF; // R
-                            buf[offset + 3] = 0xFF;
-                        }
-                    }
-                }
-            }
+        if let Some(ref hex) = self.hex_view {
+            self.render_hex_rows(hex, buf, width, text_y_start, max_visible, &colors);
+        } else {
+            self.render_text_lines(buf, width, text_y_start, max_visible, &colors);
         }
 
         // Bottom status line background
@@ -461,22 +604,22 @@ impl TextEditor {
             for dy in 0..20 {
                 let offset = ((status_y + dy) * width + x) * 4;
                 if offset + 3 < buf.len() {
-                    buf[offset] = 0x50; // B
-                    buf[offset + 1] = 0x40; // G
-                    buf[offset + 2] = 0x30; // R
-                    buf[offset + 3] = 0xFF;
+                    buf[offset..offset + 4].copy_from_slice(&bar);
                 }
             }
         }
         // Bottom status text: shortcuts hint + cursor position
         let mod_indicator = if self.modified { "*" } else { "" };
-        let file_name = self.file_path.as_deref().unwrap_or("[New File]");
+        let untitled = tr("[New File]");
+        let hint = tr("Ctrl+S Save  Ctrl+N New");
+        let file_name = self.file_path.as_deref().unwrap_or(&untitled);
         let bottom_status = format!(
-            " {}{} | Ln {}, Col {} | Ctrl+S Save  Ctrl+N New",
+            " {}{} | Ln {}, Col {} | {}",
             file_name,
             mod_indicator,
             self.cursor_line + 1,
             self.cursor_col + 1,
+            self.status_message.as_deref().unwrap_or(&hint),
         );
         draw_string_into_buffer(
             buf,
@@ -484,12 +627,103 @@ impl TextEditor {
             bottom_status.as_bytes(),
             0,
             status_y + 2,
-            0xCCCCCC,
+            colors.panel_foreground.rgb(),
         );
 
         Ok(())
     }
 
+    /// Draw the visible hex view rows.
+    fn render_hex_rows(
+        &self,
+        hex: &HexView,
+        buf: &mut [u8],
+        width: usize,
+        text_y_start: usize,
+        max_visible: usize,
+        colors: &ThemeColors,
+    ) {
+        use super::renderer::draw_string_into_buffer;
+
+        let char_h = 16;
+        for (row, bytes) in hex
+            .window
+            .chunks(HEX_ROW_BYTES)
+            .take(max_visible)
+            .enumerate()
+        {
+            let offset = ((self.scroll_line + row) * HEX_ROW_BYTES) as u64;
+            let y = text_y_start + row * char_h;
+            draw_string_into_buffer(
+                buf,
+                width,
+                hex_row(offset, bytes).as_bytes(),
+                0,
+                y,
+                colors.text_primary.rgb(),
+            );
+        }
+    }
+
+    /// Draw the visible text lines with line numbers and the cursor.
+    fn render_text_lines(
+        &self,
+        buf: &mut [u8],
+        width: usize,
+        text_y_start: usize,
+        max_visible: usize,
+        colors: &ThemeColors,
+    ) {
+        use super::renderer::{draw_glyph_into_buffer, draw_string_into_buffer};
+
+        let char_h = 16;
+        let text_x = 5 * 8; // After line number
+        let last = (self.scroll_line + max_visible).min(self.buffer.line_count());
+        for i in self.scroll_line..last {
+            let row = i - self.scroll_line;
+            let y = text_y_start + row * char_h;
+
+            // Draw line number (dim)
+            let line_num = i + 1;
+            let num_str = format!("{:>4} ", line_num);
+            draw_string_into_buffer(
+                buf,
+                width,
+                num_str.as_bytes(),
+                0,
+                y,
+                colors.text_disabled.rgb(),
+            );
+
+            // Draw text content; only the visible columns are copied out, so
+            // a single huge line costs no more than a short one
+            let head = self.buffer.line_head(i, self.visible_cols * 4);
+            let text_end = text_x + self.visible_cols * 8;
+            let mut px = text_x;
+            for ch in i18n::utf8::chars_lossy(&head) {
+                if px + 8 * usize::from(cjk::char_width(ch)) > text_end {
+                    break;
+                }
+                px += draw_glyph_into_buffer(buf, width, ch, px, y, colors.text_primary.rgb());
+            }
+
+            // Draw cursor on this line, after the cells of the text before it
+            if i == self.cursor_line {
+                let before = String::from_utf8_lossy(&head[..self.cursor_col.min(head.len())]);
+                let cursor_px = text_x + cjk::string_width(&before) * 8;
+                let cursor = colors.accent.to_bgra();
+                for dy in 0..char_h {
+                    for dx in 0..2 {
+                        let offset = ((y + dy) * width + cursor_px + dx) * 4;
+                        if offset + 3 < buf.len() {
+                            buf[offset..offset + 4].copy_from_slice(&cursor);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Get window ID
     pub fn window_id(&self) -> WindowId {
         self.window_id
@@ -0,0 +1,316 @@
+//! Sticky keys and slow keys.
+//!
+//! Accessibility filtering that sits between the keyboard decoder and the
+//! key buffer, so every consumer -- the shell, the desktop and injected
+//! on-screen keyboard input -- sees the same behaviour.
+//!
+//! - **Sticky keys**: tapping a modifier on its own latches it for the next
+//!   key; tapping it twice locks it until it is tapped a third time. A modifier
+//!   held down while another key is pressed works as usual.
+//! - **Slow keys**: a key only counts once it has been held for a minimum time;
+//!   shorter presses are dropped. Modifiers are never delayed.
+//!
+//! The filter is generic over the key type so the same logic serves the
+//! PS/2 decoder (which defers `pc_keyboard` key codes) and the unit tests.
+
+use crate::drivers::keyboard::{MOD_CTRL, MOD_SHIFT};
+
+/// Longest slow-keys delay accepted, in milliseconds.
+pub const MAX_SLOW_KEYS_MS: u32 = 2000;
+
+/// A key press waiting out the slow-keys delay.
+#[derive(Debug, Clone, Copy)]
+struct Pending<K> {
+    key: K,
+    /// When it went down, in milliseconds.
+    since: u64,
+    /// Already delivered; typematic repeats pass straight through.
+    accepted: bool,
+}
+
+/// Sticky/slow keys state machine.
+#[derive(Debug)]
+pub struct AccessKeys<K> {
+    sticky_keys: bool,
+    slow_keys_ms: u64,
+    /// Modifiers held down, on the keyboard or by an injector.
+    held: u8,
+    /// Modifiers latched for the next key.
+    latched: u8,
+    /// Modifiers locked on.
+    locked: u8,
+    /// Modifiers pressed with no other key since; releasing one of these
+    /// counts as a tap.
+    armed: u8,
+    /// The latched modifiers were used by a delivered key and drop on the
+    /// next event or poll.
+    spent: bool,
+    pending: Option<Pending<K>>,
+}
+
+impl<K: Copy + PartialEq> AccessKeys<K> {
+    /// A filter with both features off.
+    pub const fn new() -> Self {
+        Self {
+            sticky_keys: false,
+            slow_keys_ms: 0,
+            held: 0,
+            latched: 0,
+            locked: 0,
+            armed: 0,
+            spent: false,
+            pending: None,
+        }
+    }
+
+    /// Change the options. Turning sticky keys off releases latched and
+    /// locked modifiers; turning slow keys off drops a waiting key.
+    pub fn configure(&mut self, sticky_keys: bool, slow_keys_ms: u32) {
+        self.sticky_keys = sticky_keys;
+        self.slow_keys_ms = u64::from(slow_keys_ms.min(MAX_SLOW_KEYS_MS));
+        if !sticky_keys {
+            self.latched = 0;
+            self.locked = 0;
+            self.armed = 0;
+            self.spent = false;
+        }
+        if self.slow_keys_ms == 0 {
+            self.pending = None;
+        }
+    }
+
+    /// Latched and locked modifiers that are not physically held.
+    pub fn sticky_modifiers(&self) -> u8 {
+        (self.latched | self.locked) & !self.held
+    }
+
+    /// A modifier key went down or up.
+    pub fn modifier(&mut self, bit: u8, down: bool) {
+        self.release_spent();
+        if down {
+            self.held |= bit;
+            self.armed |= bit;
+            return;
+        }
+        self.held &= !bit;
+        if !self.sticky_keys || self.armed & bit == 0 {
+            return;
+        }
+        self.armed &= !bit;
+        if self.locked & bit != 0 {
+            self.locked &= !bit;
+        } else if self.latched & bit != 0 {
+            self.latched &= !bit;
+            self.locked |= bit;
+        } else {
+            self.latched |= bit;
+        }
+    }
+
+    /// A non-modifier key went down at `now` ms. Returns the key if it
+    /// should be delivered now.
+    pub fn key_down(&mut self, key: K, now: u64) -> Option<K> {
+        self.release_spent();
+        self.armed = 0;
+        if self.slow_keys_ms == 0 {
+            return Some(self.deliver(key));
+        }
+        match self.pending {
+            // Typematic repeat of the key being timed or already accepted
+            Some(p) if p.key == key => p.accepted.then(|| self.deliver(key)),
+            _ => {
+                self.pending = Some(Pending {
+                    key,
+                    since: now,
+                    accepted: false,
+                });
+                None
+            }
+        }
+    }
+
+    /// A non-modifier key went up at `now` ms. Returns the key if it was
+    /// held long enough but had not been delivered yet.
+    pub fn key_up(&mut self, key: K, now: u64) -> Option<K> {
+        self.release_spent();
+        match self.pending {
+            Some(p) if p.key == key => {
+                self.pending = None;
+                (!p.accepted && self.long_enough(p.since, now)).then(|| self.deliver(key))
+            }
+            _ => None,
+        }
+    }
+
+    /// Periodic check at `now` ms: drops spent latches and returns a
+    /// waiting key once it has been held long enough.
+    pub fn poll(&mut self, now: u64) -> Option<K> {
+        self.release_spent();
+        let p = self.pending.as_mut()?;
+        if p.accepted || now.saturating_sub(p.since) < self.slow_keys_ms {
+            return None;
+        }
+        p.accepted = true;
+        let key = p.key;
+        Some(self.deliver(key))
+    }
+
+    /// A key that bypasses the slow-keys delay, such as one injected by an
+    /// on-screen keyboard. Returns the modifiers that apply to it: held,
+    /// latched and locked.
+    pub fn injected_key(&mut self) -> u8 {
+        self.release_spent();
+        self.armed = 0;
+        let mods = self.held | self.latched | self.locked;
+        self.spent = self.latched != 0;
+        mods
+    }
+
+    fn deliver(&mut self, key: K) -> K {
+        self.spent = self.latched != 0;
+        key
+    }
+
+    fn release_spent(&mut self) {
+        if self.spent {
+            self.latched = 0;
+            self.spent = false;
+        }
+    }
+
+    fn long_enough(&self, since: u64, now: u64) -> bool {
+        now.saturating_sub(since) >= self.slow_keys_ms
+    }
+}
+
+impl<K: Copy + PartialEq> Default for AccessKeys<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply Shift and Ctrl from `mods` to a decoded ASCII byte, as the US
+/// layout would have produced it had the modifier been held.
+pub fn apply_modifiers(byte: u8, mods: u8) -> u8 {
+    let mut byte = byte;
+    if mods & MOD_SHIFT != 0 {
+        byte = shifted(byte);
+    }
+    if mods & MOD_CTRL != 0 && byte.is_ascii_alphabetic() {
+        byte &= 0x1F;
+    }
+    byte
+}
+
+/// The US-layout shifted form of `byte`.
+fn shifted(byte: u8) -> u8 {
+    match byte {
+        b'a'..=b'z' => byte.to_ascii_uppercase(),
+        b'1' => b'!',
+        b'2' => b'@',
+        b'3' => b'#',
+        b'4' => b'$',
+        b'5' => b'%',
+        b'6' => b'^',
+        b'7' => b'&',
+        b'8' => b'*',
+        b'9' => b'(',
+        b'0' => b')',
+        b'-' => b'_',
+        b'=' => b'+',
+        b'[' => b'{',
+        b']' => b'}',
+        b'\\' => b'|',
+        b';' => b':',
+        b'\'' => b'"',
+        b',' => b'<',
+        b'.' => b'>',
+        b'/' => b'?',
+        b'`' => b'~',
+        _ => byte,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::keyboard::MOD_ALT;
+
+    fn tap(keys: &mut AccessKeys<u8>, bit: u8) {
+        keys.modifier(bit, true);
+        keys.modifier(bit, false);
+    }
+
+    #[test]
+    fn test_sticky_latch_and_lock() {
+        let mut keys = AccessKeys::new();
+        keys.configure(true, 0);
+
+        tap(&mut keys, MOD_SHIFT);
+        assert_eq!(keys.sticky_modifiers(), MOD_SHIFT);
+        assert_eq!(keys.key_down(b'a', 0), Some(b'a'));
+        // Still reported until the next event so the consumer sees it
+        assert_eq!(keys.sticky_modifiers(), MOD_SHIFT);
+        assert_eq!(keys.poll(0), None);
+        assert_eq!(keys.sticky_modifiers(), 0);
+
+        tap(&mut keys, MOD_CTRL);
+        tap(&mut keys, MOD_CTRL);
+        keys.key_down(b'c', 0);
+        keys.key_down(b'c', 0);
+        assert_eq!(keys.sticky_modifiers(), MOD_CTRL);
+        tap(&mut keys, MOD_CTRL);
+        assert_eq!(keys.sticky_modifiers(), 0);
+    }
+
+    #[test]
+    fn test_sticky_chord_does_not_latch() {
+        let mut keys = AccessKeys::new();
+        keys.configure(true, 0);
+        keys.modifier(MOD_ALT, true);
+        assert_eq!(keys.sticky_modifiers(), 0);
+        keys.key_down(b'\t', 0);
+        keys.modifier(MOD_ALT, false);
+        assert_eq!(keys.sticky_modifiers(), 0);
+
+        keys.configure(false, 0);
+        tap(&mut keys, MOD_SHIFT);
+        assert_eq!(keys.sticky_modifiers(), 0);
+    }
+
+    #[test]
+    fn test_slow_keys() {
+        let mut keys = AccessKeys::new();
+        keys.configure(false, 300);
+
+        // Too short: dropped
+        assert_eq!(keys.key_down(b'x', 1000), None);
+        assert_eq!(keys.poll(1100), None);
+        assert_eq!(keys.key_up(b'x', 1200), None);
+
+        // Held long enough: accepted by the poll, repeats pass through
+        assert_eq!(keys.key_down(b'y', 2000), None);
+        assert_eq!(keys.key_down(b'y', 2100), None);
+        assert_eq!(keys.poll(2300), Some(b'y'));
+        assert_eq!(keys.key_down(b'y', 2350), Some(b'y'));
+        assert_eq!(keys.key_up(b'y', 2400), None);
+
+        // Released before a poll noticed
+        keys.key_down(b'z', 3000);
+        assert_eq!(keys.key_up(b'z', 3500), Some(b'z'));
+    }
+
+    #[test]
+    fn test_injected_key_uses_latches() {
+        let mut keys: AccessKeys<u8> = AccessKeys::new();
+        keys.configure(true, 500);
+        tap(&mut keys, MOD_SHIFT);
+        let mods = keys.injected_key();
+        assert_eq!(apply_modifiers(b'q', mods), b'Q');
+        assert_eq!(keys.injected_key(), 0);
+
+        assert_eq!(apply_modifiers(b'1', MOD_SHIFT), b'!');
+        assert_eq!(apply_modifiers(b'c', MOD_CTRL), 0x03);
+        assert_eq!(apply_modifiers(b'C', MOD_CTRL | MOD_SHIFT), 0x03);
+    }
+}
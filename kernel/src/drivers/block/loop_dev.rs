@@ -0,0 +1,194 @@
+//! Loop Block Device
+//!
+//! `loopN` exposes a regular file as a disk of 512-byte blocks: block `n`
+//! is bytes `n * 512 ..` of the file. The capacity is fixed when the file
+//! is attached; a trailing partial block is not part of the device, and
+//! reads past a file that shrank since return zeros.
+//!
+//! Writes go straight to the backing file, so they are as durable as that
+//! file's filesystem makes them.
+
+use alloc::{string::String, sync::Arc};
+
+use spin::Mutex;
+
+use crate::{
+    error::{FsError, KernelError},
+    fs::{blockdev::BlockDevice, NodeType, VfsNode},
+};
+
+/// Block size of loop devices.
+pub const BLOCK_SIZE: usize = 512;
+
+/// A regular file seen as a block device.
+pub struct LoopDevice {
+    name: String,
+    file: Arc<dyn VfsNode>,
+    block_count: u64,
+    read_only: bool,
+}
+
+impl LoopDevice {
+    /// Wrap `file`, which must be a regular file of at least one block.
+    pub fn new(name: String, file: Arc<dyn VfsNode>, read_only: bool) -> Result<Self, KernelError> {
+        let metadata = file.metadata()?;
+        if metadata.node_type != NodeType::File {
+            return Err(KernelError::FsError(FsError::NotAFile));
+        }
+        let block_count = (metadata.size / BLOCK_SIZE) as u64;
+        if block_count == 0 {
+            return Err(KernelError::InvalidArgument {
+                name: "file",
+                value: "smaller than one block",
+            });
+        }
+        Ok(Self {
+            name,
+            file,
+            block_count,
+            read_only,
+        })
+    }
+
+    /// Byte offset of a transfer, checked against the device size.
+    fn offset(&self, start_block: u64, len: usize) -> Result<usize, KernelError> {
+        let blocks = (len / BLOCK_SIZE) as u64;
+        if !len.is_multiple_of(BLOCK_SIZE)
+            || start_block
+                .checked_add(blocks)
+                .is_none_or(|end| end > self.block_count)
+        {
+            return Err(KernelError::InvalidArgument {
+                name: "block_range",
+                value: "out_of_bounds",
+            });
+        }
+        Ok(start_block as usize * BLOCK_SIZE)
+    }
+}
+
+impl BlockDevice for LoopDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn block_count(&self) -> u64 {
+        self.block_count
+    }
+
+    fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<(), KernelError> {
+        let offset = self.offset(start_block, buffer.len())?;
+        let mut done = 0;
+        while done < buffer.len() {
+            let n = self.file.read(offset + done, &mut buffer[done..])?;
+            if n == 0 {
+                buffer[done..].fill(0);
+                break;
+            }
+            done += n;
+        }
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, start_block: u64, buffer: &[u8]) -> Result<(), KernelError> {
+        if self.read_only {
+            return Err(KernelError::FsError(FsError::ReadOnly));
+        }
+        let offset = self.offset(start_block, buffer.len())?;
+        let mut done = 0;
+        while done < buffer.len() {
+            let n = self.file.write(offset + done, &buffer[done..])?;
+            if n == 0 {
+                return Err(KernelError::FsError(FsError::IoError));
+            }
+            done += n;
+        }
+        Ok(())
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+/// Attach the file at `path` to the first free loop device and return the
+/// device's name.
+pub fn attach(path: &str, read_only: bool) -> Result<String, KernelError> {
+    let vfs = crate::fs::try_get_vfs().ok_or(KernelError::NotInitialized { subsystem: "VFS" })?;
+    let file = vfs.read().resolve_path(path)?;
+    let name = super::next_free_name("loop");
+    let device = LoopDevice::new(name.clone(), file, read_only)?;
+    super::register(Arc::new(Mutex::new(device)))?;
+    crate::println!(
+        "[LOOP] {} attached to {}{}",
+        name,
+        path,
+        if read_only { " (read-only)" } else { "" }
+    );
+    Ok(name)
+}
+
+/// Detach a loop device, e.g. `loop0` or `/dev/loop0`.
+pub fn detach(name: &str) -> Result<(), KernelError> {
+    if !name.trim_start_matches("/dev/").starts_with("loop") {
+        return Err(KernelError::InvalidArgument {
+            name: "device",
+            value: "not a loop device",
+        });
+    }
+    super::unregister(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::fs::{ramfs::RamFs, Filesystem, Permissions};
+
+    fn image(len: usize) -> Arc<dyn VfsNode> {
+        let fs = RamFs::new();
+        let file = fs
+            .root()
+            .create("disk.img", Permissions::default())
+            .unwrap();
+        file.write(0, &vec![0xA5u8; len]).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_loop_device_size() {
+        let dev =
+            LoopDevice::new(String::from("loop-t"), image(4 * BLOCK_SIZE + 100), false).unwrap();
+        assert_eq!(dev.block_count(), 4);
+        assert!(LoopDevice::new(String::from("loop-t"), image(100), false).is_err());
+    }
+
+    #[test]
+    fn test_loop_device_read_write() {
+        let mut dev =
+            LoopDevice::new(String::from("loop-t"), image(4 * BLOCK_SIZE), false).unwrap();
+        let data = [0x11u8; BLOCK_SIZE];
+        dev.write_blocks(1, &data).unwrap();
+
+        let mut buf = [0u8; 2 * BLOCK_SIZE];
+        dev.read_blocks(0, &mut buf).unwrap();
+        assert_eq!(&buf[..BLOCK_SIZE], &[0xA5u8; BLOCK_SIZE][..]);
+        assert_eq!(&buf[BLOCK_SIZE..], &data[..]);
+        assert!(dev.read_blocks(3, &mut buf).is_err());
+    }
+
+    #[test]
+    fn test_loop_device_read_only() {
+        let mut dev = LoopDevice::new(String::from("loop-t"), image(BLOCK_SIZE), true).unwrap();
+        assert!(dev.is_read_only());
+        assert_eq!(
+            dev.write_blocks(0, &[0u8; BLOCK_SIZE]),
+            Err(KernelError::FsError(FsError::ReadOnly))
+        );
+    }
+}
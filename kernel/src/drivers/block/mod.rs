@@ -0,0 +1,340 @@
+//! Virtual Block Devices
+//!
+//! Block devices with no controller behind them:
+//! - `loopN`: a regular file exposed as a disk ([`loop_dev`]), so images made
+//!   by `mkfs-blockfs` can be mounted on a running system
+//! - `nullbN`: a disk that completes every request without doing any I/O
+//!   ([`null`]), for measuring the block layer itself
+//! - `ramN`: a disk in memory ([`ram`]), for tests and early boot
+//!
+//! Each device is registered with the driver framework as a `Block` device
+//! on the `virtual` bus, bound to its own [`BlockDriver`], gets a
+//! `/dev/<name>` node, and can be looked up by name (with or without a
+//! `/dev/` prefix) through [`get`]. Its I/O goes through a request queue
+//! of the multi-queue block layer ([`mq`]), found with [`queue`].
+
+pub mod loop_dev;
+pub mod mq;
+pub mod null;
+pub mod ram;
+
+use alloc::{boxed::Box, collections::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
+
+use spin::{Mutex, RwLock};
+
+use self::mq::{BlockQueue, Op};
+use crate::{
+    error::KernelError,
+    fs::blockdev::BlockDevice,
+    services::driver_framework::{self, DeviceClass, DeviceInfo, DeviceStatus, Driver, PowerState},
+};
+
+/// A block device shared between its driver and filesystems mounted on it.
+pub type SharedBlockDevice = Arc<Mutex<dyn BlockDevice>>;
+
+/// Bus name the virtual block devices are registered on.
+const VIRTUAL_BUS: &str = "virtual";
+
+/// Major device number per name prefix; the minor is the device's index.
+const MAJORS: &[(&str, u32)] = &[("loop", 7), ("nullb", 252), ("ram", 1)];
+
+/// `ioctl`: capacity in bytes.
+pub const BLKGETSIZE64: u32 = 0x3000;
+/// `ioctl`: block size in bytes.
+pub const BLKSSZGET: u32 = 0x3001;
+/// `ioctl`: flush cached writes.
+pub const BLKFLSBUF: u32 = 0x3002;
+
+crate::kernel_param! {
+    /// I/O scheduler of the block queues: `none`, `deadline`, or empty for
+    /// `deadline` on single-queue devices and `none` on the others.
+    pub static SCHEDULER: String = String::new(), "block.scheduler", on_set = set_scheduler;
+}
+
+struct Registered {
+    /// Driver framework device ID, 0 without a driver framework.
+    device_id: u64,
+    device: SharedBlockDevice,
+    queue: Arc<BlockQueue>,
+}
+
+static DEVICES: RwLock<BTreeMap<String, Registered>> = RwLock::new(BTreeMap::new());
+
+/// Driver framework binding for one virtual block device.
+///
+/// Reads and writes take byte offsets and must cover whole blocks.
+pub struct BlockDriver {
+    name: String,
+    queue: Arc<BlockQueue>,
+}
+
+impl BlockDriver {
+    /// Create the driver for `device`, named after it.
+    pub fn new(device: SharedBlockDevice) -> Self {
+        Self::with_queue(Arc::new(BlockQueue::new(device)))
+    }
+
+    /// Create the driver for the device behind `queue`.
+    pub fn with_queue(queue: Arc<BlockQueue>) -> Self {
+        let name = String::from(queue.device().lock().name());
+        Self { name, queue }
+    }
+
+    /// Flush the device, after the writes queued before.
+    fn flush(&self) -> Result<(), KernelError> {
+        self.queue.execute(Op::Flush, 0, Vec::new()).map(drop)
+    }
+
+    /// First block of a transfer at `offset` of `len` bytes.
+    fn start_block(&self, offset: u64, len: usize) -> Result<u64, KernelError> {
+        let block_size = self.queue.block_size() as u64;
+        if !offset.is_multiple_of(block_size) || !(len as u64).is_multiple_of(block_size) {
+            return Err(KernelError::InvalidArgument {
+                name: "offset",
+                value: "must cover whole blocks",
+            });
+        }
+        Ok(offset / block_size)
+    }
+}
+
+impl Driver for BlockDriver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supported_classes(&self) -> Vec<DeviceClass> {
+        vec![DeviceClass::Block]
+    }
+
+    fn supports_device(&self, device: &DeviceInfo) -> bool {
+        device.class == DeviceClass::Block && device.bus == VIRTUAL_BUS && device.name == self.name
+    }
+
+    fn probe(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    fn attach(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    fn detach(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        self.flush()
+    }
+
+    fn suspend(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        self.flush()
+    }
+
+    fn resume(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    fn handle_interrupt(&mut self, _irq: u8) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize, KernelError> {
+        let start = self.start_block(offset, buffer.len())?;
+        let data = self.queue.execute(Op::Read, start, vec![0; buffer.len()])?;
+        buffer.copy_from_slice(&data);
+        Ok(buffer.len())
+    }
+
+    fn write(&mut self, offset: u64, data: &[u8]) -> Result<usize, KernelError> {
+        let start = self.start_block(offset, data.len())?;
+        self.queue.execute(Op::Write, start, data.to_vec())?;
+        Ok(data.len())
+    }
+
+    fn ioctl(&mut self, cmd: u32, _arg: u64) -> Result<u64, KernelError> {
+        match cmd {
+            BLKGETSIZE64 => {
+                let device = self.queue.device().lock();
+                Ok(device.block_count() * device.block_size() as u64)
+            }
+            BLKSSZGET => Ok(self.queue.block_size() as u64),
+            BLKFLSBUF => {
+                self.flush()?;
+                Ok(0)
+            }
+            _ => Err(KernelError::InvalidArgument {
+                name: "ioctl_cmd",
+                value: "unknown",
+            }),
+        }
+    }
+}
+
+/// Device name without a leading `/dev/`.
+fn device_name(path: &str) -> &str {
+    path.strip_prefix("/dev/").unwrap_or(path)
+}
+
+/// Major and minor number of the device called `name`.
+fn device_numbers(name: &str) -> (u32, u32) {
+    MAJORS
+        .iter()
+        .find_map(|&(prefix, major)| {
+            let minor = name.strip_prefix(prefix)?.parse().ok()?;
+            Some((major, minor))
+        })
+        .unwrap_or((0, 0))
+}
+
+/// Register a block device and bind a [`BlockDriver`] to it.
+///
+/// Returns the driver framework device ID (0 before the framework is up).
+pub fn register(device: SharedBlockDevice) -> Result<u64, KernelError> {
+    let name = String::from(device.lock().name());
+    let mut devices = DEVICES.write();
+    if devices.contains_key(&name) {
+        return Err(KernelError::AlreadyExists {
+            resource: "block device",
+            id: 0,
+        });
+    }
+
+    let queue = Arc::new(BlockQueue::new(device.clone()));
+    let mut device_id = 0;
+    if let Some(framework) = driver_framework::try_get_driver_framework() {
+        framework.register_driver(Box::new(BlockDriver::with_queue(queue.clone())))?;
+        let info = DeviceInfo {
+            id: 0,
+            name: name.clone(),
+            class: DeviceClass::Block,
+            device_id: None,
+            driver: None,
+            bus: String::from(VIRTUAL_BUS),
+            address: 0,
+            irq: None,
+            dma_channels: Vec::new(),
+            io_ports: Vec::new(),
+            memory_regions: Vec::new(),
+            status: DeviceStatus::Uninitialized,
+            parent: None,
+            power: PowerState::On,
+        };
+        device_id = match framework.add_device(info) {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = framework.unregister_driver(&name);
+                return Err(e);
+            }
+        };
+    }
+
+    let (major, minor) = device_numbers(&name);
+    crate::fs::devfs::register_block_device(&name, major, minor);
+    devices.insert(
+        name,
+        Registered {
+            device_id,
+            device,
+            queue,
+        },
+    );
+    Ok(device_id)
+}
+
+/// Flush and remove a block device.
+pub fn unregister(name: &str) -> Result<(), KernelError> {
+    let name = device_name(name);
+    let registered = DEVICES.write().remove(name).ok_or(KernelError::NotFound {
+        resource: "block device",
+        id: 0,
+    })?;
+    crate::fs::devfs::unregister_device(name);
+
+    if let Some(framework) = driver_framework::try_get_driver_framework() {
+        if registered.device_id != 0 {
+            framework.remove_device(registered.device_id)?;
+        }
+        framework.unregister_driver(name)?;
+    } else {
+        registered.device.lock().flush()?;
+    }
+    Ok(())
+}
+
+/// Look up a registered block device by name, e.g. `loop0` or `/dev/loop0`.
+pub fn get(name: &str) -> Option<SharedBlockDevice> {
+    DEVICES
+        .read()
+        .get(device_name(name))
+        .map(|registered| registered.device.clone())
+}
+
+/// The request queue of a registered block device.
+pub fn queue(name: &str) -> Option<Arc<BlockQueue>> {
+    DEVICES
+        .read()
+        .get(device_name(name))
+        .map(|registered| registered.queue.clone())
+}
+
+/// Switch every block queue to the scheduler `block.scheduler` names.
+#[allow(clippy::ptr_arg)] // `on_set` hooks take the parameter's own type
+fn set_scheduler(name: &String) {
+    for (device, registered) in DEVICES.read().iter() {
+        if let Err(_e) = registered.queue.set_scheduler(name) {
+            crate::println!("[BLOCK] {}: {:?}", device, _e);
+        }
+    }
+}
+
+/// Names of all registered block devices.
+pub fn names() -> Vec<String> {
+    DEVICES.read().keys().cloned().collect()
+}
+
+/// Lowest `<prefix>N` name not in use.
+pub(crate) fn next_free_name(prefix: &str) -> String {
+    let devices = DEVICES.read();
+    (0u32..)
+        .map(|n| alloc::format!("{}{}", prefix, n))
+        .find(|name| !devices.contains_key(name))
+        .unwrap_or_default()
+}
+
+/// Create the boot-time virtual block devices.
+pub fn init() {
+    null::init();
+    ram::init();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{null::NullBlockDevice, *};
+
+    #[test]
+    fn test_device_name() {
+        assert_eq!(device_name("/dev/loop0"), "loop0");
+        assert_eq!(device_name("nullb1"), "nullb1");
+    }
+
+    #[test]
+    fn test_device_numbers() {
+        assert_eq!(device_numbers("loop3"), (7, 3));
+        assert_eq!(device_numbers("ram0"), (1, 0));
+        assert_eq!(device_numbers("nullb1"), (252, 1));
+        assert_eq!(device_numbers("sda"), (0, 0));
+    }
+
+    #[test]
+    fn test_block_driver_rejects_partial_blocks() {
+        let device: SharedBlockDevice = Arc::new(Mutex::new(NullBlockDevice::new(
+            String::from("nullb-t"),
+            512,
+            8,
+        )));
+        let mut driver = BlockDriver::new(device);
+        let mut buf = [0u8; 1024];
+        assert_eq!(driver.read(512, &mut buf), Ok(1024));
+        assert!(driver.read(100, &mut buf).is_err());
+        assert!(driver.write(0, &buf[..100]).is_err());
+        assert_eq!(driver.ioctl(BLKGETSIZE64, 0), Ok(4096));
+        assert_eq!(driver.ioctl(BLKSSZGET, 0), Ok(512));
+    }
+}
@@ -0,0 +1,712 @@
+//! Multi-queue block layer
+//!
+//! Every registered block device gets a [`BlockQueue`], through which its
+//! requests pass on their way to the driver:
+//!
+//! ```text
+//!   CPU 0    CPU 1    CPU 2    CPU 3     software queues, one per CPU
+//!      \      /          \      /
+//!     scheduler          scheduler        I/O scheduler per hardware queue
+//!         |                  |
+//!   hardware queue     hardware queue     tags: requests in flight
+//!          \                /
+//!               driver
+//! ```
+//!
+//! A request is put on the software queue of the CPU submitting it, which
+//! no other CPU touches unless it shares the hardware queue; CPUs map onto
+//! the device's hardware queues ([`BlockDevice::hw_queues`]) round robin.
+//! Running a hardware queue moves the requests of its CPUs into its I/O
+//! scheduler and dispatches as many as it has tags, one per request the
+//! device can hold ([`BlockDevice::queue_depth`]).
+//!
+//! A request completes on the CPU that submitted it. When the driver
+//! finishes it elsewhere, the result is handed to that CPU's completion
+//! list and its `kworker` picks it up; a submitter waiting on the request
+//! drains its own list as well.
+//!
+//! The I/O scheduler is `none` (first come, first served) or `deadline`
+//! (requests sorted by block, reads preferred, every request dispatched
+//! by its deadline), chosen with `block.scheduler=`. Left empty, a device
+//! with one hardware queue gets `deadline` and one with several, which
+//! schedules for itself, `none`. Either way a flush is a barrier: nothing
+//! queued before it is dispatched after it, nor the other way round.
+//!
+//! [`BlockDevice::hw_queues`]: crate::fs::blockdev::BlockDevice::hw_queues
+//! [`BlockDevice::queue_depth`]: crate::fs::blockdev::BlockDevice::queue_depth
+
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{
+    ops::Bound,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use spin::Mutex;
+
+use super::SharedBlockDevice;
+use crate::{
+    error::KernelError,
+    sched::{
+        smp::MAX_CPUS,
+        workqueue::{self, Work},
+    },
+};
+
+/// Most requests in flight on one hardware queue.
+pub const MAX_QUEUE_DEPTH: usize = 256;
+
+/// `deadline`: time a read may wait before it is dispatched.
+const READ_EXPIRE_MS: u64 = 500;
+/// `deadline`: time a write may wait before it is dispatched.
+const WRITE_EXPIRE_MS: u64 = 5000;
+/// `deadline`: requests dispatched in one direction in a row.
+const FIFO_BATCH: u32 = 16;
+/// `deadline`: read batches that may pass over waiting writes.
+const WRITES_STARVED: u32 = 2;
+
+const READ: usize = 0;
+const WRITE: usize = 1;
+
+/// What a request does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Read,
+    Write,
+    /// Write with Force Unit Access.
+    WriteFua,
+    /// Flush the device's write cache.
+    Flush,
+}
+
+/// Where the result of a request goes.
+#[derive(Default)]
+struct Done {
+    ready: AtomicBool,
+    result: Mutex<Option<Result<Vec<u8>, KernelError>>>,
+}
+
+/// An I/O request.
+pub struct Request {
+    pub op: Op,
+    /// First block.
+    pub block: u64,
+    /// Data to write, or the buffer to read into: whole blocks.
+    pub data: Vec<u8>,
+    /// The submitting CPU, on which the request completes.
+    pub cpu: usize,
+    done: Arc<Done>,
+}
+
+impl Request {
+    /// A request from the current CPU.
+    pub fn new(op: Op, block: u64, data: Vec<u8>) -> Self {
+        Self {
+            op,
+            block,
+            data,
+            cpu: current_cpu(),
+            done: Arc::default(),
+        }
+    }
+}
+
+/// A submitted request.
+pub struct Pending(Arc<Done>);
+
+impl Pending {
+    /// Whether the request has completed.
+    pub fn is_done(&self) -> bool {
+        self.0.ready.load(Ordering::Acquire)
+    }
+
+    /// Wait for the request to complete. Returns its data: what was read,
+    /// for a read.
+    pub fn wait(self) -> Result<Vec<u8>, KernelError> {
+        while !self.is_done() {
+            // The result may be on its way to this very CPU
+            complete_steered(current_cpu());
+            core::hint::spin_loop();
+        }
+        self.0
+            .result
+            .lock()
+            .take()
+            .unwrap_or(Err(KernelError::InvalidState {
+                expected: "request result",
+                actual: "already taken",
+            }))
+    }
+}
+
+fn current_cpu() -> usize {
+    (crate::sched::smp::current_cpu_id() as usize).min(MAX_CPUS - 1)
+}
+
+// ---------------------------------------------------------------------------
+// Completion steering
+// ---------------------------------------------------------------------------
+
+type Completed = (Arc<Done>, Result<Vec<u8>, KernelError>);
+
+/// Results waiting for the CPU that submitted them.
+static STEERED: [Mutex<VecDeque<Completed>>; MAX_CPUS] =
+    [const { Mutex::new(VecDeque::new()) }; MAX_CPUS];
+
+static STEER_WORK: [Work; MAX_CPUS] = [const { Work::new(steer_work, 0) }; MAX_CPUS];
+
+fn finish(done: &Done, result: Result<Vec<u8>, KernelError>) {
+    *done.result.lock() = Some(result);
+    done.ready.store(true, Ordering::Release);
+}
+
+/// Complete `request` on the CPU that submitted it.
+fn complete(request: Request, result: Result<Vec<u8>, KernelError>) {
+    let cpu = request.cpu;
+    if cpu == current_cpu() {
+        finish(&request.done, result);
+        return;
+    }
+    STEERED[cpu].lock().push_back((request.done, result));
+    workqueue::queue_work_on(cpu as u8, &STEER_WORK[cpu]);
+}
+
+/// Complete the requests steered to `cpu`.
+fn complete_steered(cpu: usize) {
+    loop {
+        let Some((done, result)) = STEERED[cpu].lock().pop_front() else {
+            return;
+        };
+        finish(&done, result);
+    }
+}
+
+fn steer_work(_data: usize) {
+    complete_steered(current_cpu());
+}
+
+// ---------------------------------------------------------------------------
+// Tags
+// ---------------------------------------------------------------------------
+
+/// The tags of a hardware queue: which of its slots hold a request.
+pub struct TagSet {
+    words: Vec<u64>,
+    depth: usize,
+    used: usize,
+}
+
+impl TagSet {
+    pub fn new(depth: usize) -> Self {
+        let depth = depth.clamp(1, MAX_QUEUE_DEPTH);
+        Self {
+            words: vec![0; depth.div_ceil(64)],
+            depth,
+            used: 0,
+        }
+    }
+
+    /// Take the lowest free tag.
+    pub fn alloc(&mut self) -> Option<u16> {
+        let (index, word) = self
+            .words
+            .iter_mut()
+            .enumerate()
+            .find(|(_, w)| **w != u64::MAX)?;
+        let tag = index * 64 + word.trailing_ones() as usize;
+        if tag >= self.depth {
+            return None;
+        }
+        *word |= 1 << (tag % 64);
+        self.used += 1;
+        Some(tag as u16)
+    }
+
+    pub fn free(&mut self, tag: u16) {
+        let (index, bit) = (tag as usize / 64, tag % 64);
+        if let Some(word) = self.words.get_mut(index) {
+            if *word & (1 << bit) != 0 {
+                *word &= !(1 << bit);
+                self.used -= 1;
+            }
+        }
+    }
+
+    /// Tags taken.
+    pub fn in_use(&self) -> usize {
+        self.used
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+// ---------------------------------------------------------------------------
+// I/O schedulers
+// ---------------------------------------------------------------------------
+
+/// Orders the requests of a hardware queue.
+pub trait IoScheduler: Send {
+    fn name(&self) -> &'static str;
+
+    /// Queue a request, at `now_ms`.
+    fn insert(&mut self, request: Request, now_ms: u64);
+
+    /// The next request to hand to the device.
+    fn dispatch(&mut self, now_ms: u64) -> Option<Request>;
+
+    fn is_empty(&self) -> bool;
+}
+
+/// `none`: requests go to the device in the order they came.
+#[derive(Default)]
+pub struct NoneScheduler {
+    fifo: VecDeque<Request>,
+}
+
+impl IoScheduler for NoneScheduler {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn insert(&mut self, request: Request, _now_ms: u64) {
+        self.fifo.push_back(request);
+    }
+
+    fn dispatch(&mut self, _now_ms: u64) -> Option<Request> {
+        self.fifo.pop_front()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.fifo.is_empty()
+    }
+}
+
+/// Sort key of a queued request: its block, then its arrival.
+type Key = (u64, u64);
+
+/// `deadline`: requests go in block order, in batches of reads or writes,
+/// reads first unless writes have waited [`WRITES_STARVED`] batches. A
+/// batch starts with the oldest request instead once that one has
+/// waited past its deadline.
+#[derive(Default)]
+pub struct Deadline {
+    /// Reads and writes by key.
+    sorted: [BTreeMap<Key, Request>; 2],
+    /// Keys in arrival order, with their deadline.
+    fifo: [VecDeque<(u64, Key)>; 2],
+    /// Key of the request dispatched last.
+    last: Key,
+    /// Direction of the current batch, and its length so far.
+    dir: usize,
+    batch: u32,
+    /// Read batches started while writes were waiting.
+    starved: u32,
+    seq: u64,
+    /// A flush waiting for the requests queued before it, and the requests
+    /// queued after it.
+    barrier: Option<Request>,
+    held: VecDeque<Request>,
+}
+
+impl Deadline {
+    fn after(&self, dir: usize, key: Key) -> Option<Key> {
+        self.sorted[dir]
+            .range((Bound::Excluded(key), Bound::Unbounded))
+            .next()
+            .map(|(k, _)| *k)
+    }
+
+    fn take(&mut self, dir: usize, key: Key) -> Option<Request> {
+        let request = self.sorted[dir].remove(&key)?;
+        self.fifo[dir].retain(|(_, k)| *k != key);
+        self.last = key;
+        self.batch += 1;
+        Some(request)
+    }
+
+    fn dispatch_sorted(&mut self, now_ms: u64) -> Option<Request> {
+        if self.batch < FIFO_BATCH {
+            if let Some(key) = self.after(self.dir, self.last) {
+                return self.take(self.dir, key);
+            }
+        }
+
+        let reads = !self.sorted[READ].is_empty();
+        let writes = !self.sorted[WRITE].is_empty();
+        let dir = if reads && (!writes || self.starved < WRITES_STARVED) {
+            if writes {
+                self.starved += 1;
+            }
+            READ
+        } else if writes {
+            self.starved = 0;
+            WRITE
+        } else {
+            return None;
+        };
+        self.dir = dir;
+        self.batch = 0;
+
+        let &(deadline, oldest) = self.fifo[dir].front()?;
+        let key = if deadline <= now_ms {
+            oldest
+        } else {
+            self.after(dir, self.last).unwrap_or(oldest)
+        };
+        self.take(dir, key)
+    }
+}
+
+impl IoScheduler for Deadline {
+    fn name(&self) -> &'static str {
+        "deadline"
+    }
+
+    fn insert(&mut self, request: Request, now_ms: u64) {
+        if self.barrier.is_some() {
+            self.held.push_back(request);
+            return;
+        }
+        let (dir, expire) = match request.op {
+            Op::Flush => {
+                self.barrier = Some(request);
+                return;
+            }
+            Op::Read => (READ, READ_EXPIRE_MS),
+            Op::Write | Op::WriteFua => (WRITE, WRITE_EXPIRE_MS),
+        };
+        let key = (request.block, self.seq);
+        self.seq += 1;
+        self.sorted[dir].insert(key, request);
+        self.fifo[dir].push_back((now_ms.saturating_add(expire), key));
+    }
+
+    fn dispatch(&mut self, now_ms: u64) -> Option<Request> {
+        if let Some(request) = self.dispatch_sorted(now_ms) {
+            return Some(request);
+        }
+        // Everything before the flush is out: the flush goes, then what
+        // waited behind it, up to the next flush
+        let flush = self.barrier.take()?;
+        for request in core::mem::take(&mut self.held) {
+            self.insert(request, now_ms);
+        }
+        Some(flush)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.barrier.is_none() && self.sorted.iter().all(BTreeMap::is_empty)
+    }
+}
+
+/// The scheduler called `name`, for a device with `hw_queues` hardware
+/// queues; empty picks one for it.
+pub fn scheduler(name: &str, hw_queues: usize) -> Result<Box<dyn IoScheduler>, KernelError> {
+    match name {
+        "none" => Ok(Box::new(NoneScheduler::default())),
+        "deadline" => Ok(Box::new(Deadline::default())),
+        "" if hw_queues > 1 => Ok(Box::new(NoneScheduler::default())),
+        "" => Ok(Box::new(Deadline::default())),
+        _ => Err(KernelError::InvalidArgument {
+            name: "block.scheduler",
+            value: "not none or deadline",
+        }),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Block queues
+// ---------------------------------------------------------------------------
+
+/// A hardware queue.
+struct HwQueue {
+    tags: TagSet,
+    scheduler: Box<dyn IoScheduler>,
+    /// Requests holding a tag, in dispatch order.
+    in_flight: VecDeque<(u16, Request)>,
+}
+
+/// The request queue of a block device.
+pub struct BlockQueue {
+    device: SharedBlockDevice,
+    block_size: usize,
+    block_count: u64,
+    /// One per CPU.
+    sw_queues: Vec<Mutex<VecDeque<Request>>>,
+    hw_queues: Vec<Mutex<HwQueue>>,
+}
+
+impl BlockQueue {
+    /// A queue for `device`, with the `block.scheduler` scheduler.
+    pub fn new(device: SharedBlockDevice) -> Self {
+        let (block_size, block_count, nr_hw, depth) = {
+            let device = device.lock();
+            (
+                device.block_size(),
+                device.block_count(),
+                device.hw_queues().clamp(1, MAX_CPUS),
+                device.queue_depth(),
+            )
+        };
+        let name = super::SCHEDULER.get();
+        let hw_queues = (0..nr_hw)
+            .map(|_| {
+                let scheduler = scheduler(&name, nr_hw)
+                    .unwrap_or_else(|_| Box::new(NoneScheduler::default()) as Box<dyn IoScheduler>);
+                Mutex::new(HwQueue {
+                    tags: TagSet::new(depth),
+                    scheduler,
+                    in_flight: VecDeque::new(),
+                })
+            })
+            .collect();
+        Self {
+            device,
+            block_size,
+            block_count,
+            sw_queues: (0..MAX_CPUS).map(|_| Mutex::new(VecDeque::new())).collect(),
+            hw_queues,
+        }
+    }
+
+    pub fn device(&self) -> &SharedBlockDevice {
+        &self.device
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn nr_hw_queues(&self) -> usize {
+        self.hw_queues.len()
+    }
+
+    /// Hardware queue the requests of `cpu` go to.
+    pub fn hw_queue_of(&self, cpu: usize) -> usize {
+        cpu % self.hw_queues.len()
+    }
+
+    /// Name of the I/O scheduler.
+    pub fn scheduler(&self) -> &'static str {
+        self.hw_queues[0].lock().scheduler.name()
+    }
+
+    /// Switch to the I/O scheduler called `name` (empty for the default),
+    /// keeping the requests queued.
+    pub fn set_scheduler(&self, name: &str) -> Result<(), KernelError> {
+        let now = crate::arch::timer::get_timestamp_ms();
+        for hw in &self.hw_queues {
+            let mut new = scheduler(name, self.hw_queues.len())?;
+            let mut hw = hw.lock();
+            while let Some(request) = hw.scheduler.dispatch(u64::MAX) {
+                new.insert(request, now);
+            }
+            hw.scheduler = new;
+        }
+        Ok(())
+    }
+
+    fn check(&self, op: Op, block: u64, len: usize) -> Result<(), KernelError> {
+        let invalid = |value| KernelError::InvalidArgument {
+            name: "block_range",
+            value,
+        };
+        if op == Op::Flush {
+            return Ok(());
+        }
+        if len == 0 || !len.is_multiple_of(self.block_size) {
+            return Err(invalid("not whole blocks"));
+        }
+        let blocks = (len / self.block_size) as u64;
+        if block
+            .checked_add(blocks)
+            .is_none_or(|end| end > self.block_count)
+        {
+            return Err(invalid("out_of_bounds"));
+        }
+        Ok(())
+    }
+
+    /// Queue a request on the current CPU and run its hardware queue.
+    pub fn submit(&self, op: Op, block: u64, data: Vec<u8>) -> Result<Pending, KernelError> {
+        self.check(op, block, data.len())?;
+        let request = Request::new(op, block, data);
+        let pending = Pending(request.done.clone());
+        let cpu = request.cpu;
+        self.sw_queues[cpu].lock().push_back(request);
+        self.run(self.hw_queue_of(cpu));
+        Ok(pending)
+    }
+
+    /// Submit a request and wait for it.
+    pub fn execute(&self, op: Op, block: u64, data: Vec<u8>) -> Result<Vec<u8>, KernelError> {
+        self.submit(op, block, data)?.wait()
+    }
+
+    /// Run hardware queue `index`: gather the requests of its CPUs, then
+    /// dispatch them as tags allow until the scheduler has none left.
+    fn run(&self, index: usize) {
+        let now = crate::arch::timer::get_timestamp_ms();
+        let mut guard = self.hw_queues[index].lock();
+        let hw = &mut *guard;
+        for cpu in (index..MAX_CPUS).step_by(self.hw_queues.len()) {
+            let queued = core::mem::take(&mut *self.sw_queues[cpu].lock());
+            for request in queued {
+                hw.scheduler.insert(request, now);
+            }
+        }
+
+        loop {
+            while let Some(tag) = hw.tags.alloc() {
+                match hw.scheduler.dispatch(now) {
+                    Some(request) => hw.in_flight.push_back((tag, request)),
+                    None => {
+                        hw.tags.free(tag);
+                        break;
+                    }
+                }
+            }
+            if hw.in_flight.is_empty() {
+                return;
+            }
+            while let Some((tag, mut request)) = hw.in_flight.pop_front() {
+                let result = self.issue(&mut request);
+                hw.tags.free(tag);
+                complete(request, result);
+            }
+        }
+    }
+
+    /// Hand `request` to the driver.
+    fn issue(&self, request: &mut Request) -> Result<Vec<u8>, KernelError> {
+        let mut device = self.device.lock();
+        match request.op {
+            Op::Read => device.read_blocks(request.block, &mut request.data),
+            Op::Write => device.write_blocks(request.block, &request.data),
+            Op::WriteFua => device.write_blocks_fua(request.block, &request.data),
+            Op::Flush => device.flush(),
+        }?;
+        Ok(core::mem::take(&mut request.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::*;
+    use crate::fs::blockdev::RamBlockDevice;
+
+    fn blocks(scheduler: &mut dyn IoScheduler, now_ms: u64) -> Vec<(Op, u64)> {
+        core::iter::from_fn(|| scheduler.dispatch(now_ms))
+            .map(|r| (r.op, r.block))
+            .collect()
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut tags = TagSet::new(70);
+        for expected in 0..70 {
+            assert_eq!(tags.alloc(), Some(expected));
+        }
+        assert_eq!(tags.alloc(), None);
+        tags.free(65);
+        tags.free(3);
+        assert_eq!(tags.in_use(), 68);
+        assert_eq!(tags.alloc(), Some(3));
+        assert_eq!(tags.alloc(), Some(65));
+        assert_eq!(TagSet::new(0).depth(), 1);
+    }
+
+    #[test]
+    fn test_deadline_order() {
+        let mut deadline = Deadline::default();
+        for block in [30, 10, 20] {
+            deadline.insert(Request::new(Op::Write, block, Vec::new()), 0);
+        }
+        for block in [7, 5] {
+            deadline.insert(Request::new(Op::Read, block, Vec::new()), 0);
+        }
+        // Reads first, each direction in block order
+        assert_eq!(
+            blocks(&mut deadline, 0),
+            [
+                (Op::Read, 5),
+                (Op::Read, 7),
+                (Op::Write, 10),
+                (Op::Write, 20),
+                (Op::Write, 30)
+            ]
+        );
+
+        assert!(deadline.is_empty());
+
+        // Past its deadline, the oldest request starts the batch
+        let mut late = Deadline::default();
+        let mut none = NoneScheduler::default();
+        for block in [30, 10] {
+            late.insert(Request::new(Op::Write, block, Vec::new()), 0);
+            none.insert(Request::new(Op::Write, block, Vec::new()), 0);
+        }
+        assert_eq!(blocks(&mut late, WRITE_EXPIRE_MS)[0], (Op::Write, 30));
+        assert_eq!(blocks(&mut none, 0), [(Op::Write, 30), (Op::Write, 10)]);
+    }
+
+    #[test]
+    fn test_deadline_flush_barrier() {
+        let mut deadline = Deadline::default();
+        for (op, block) in [
+            (Op::Write, 50),
+            (Op::Flush, 0),
+            (Op::Write, 1),
+            (Op::Read, 2),
+            (Op::Flush, 0),
+            (Op::WriteFua, 0),
+        ] {
+            deadline.insert(Request::new(op, block, Vec::new()), 0);
+        }
+        assert_eq!(
+            blocks(&mut deadline, 0),
+            [
+                (Op::Write, 50),
+                (Op::Flush, 0),
+                (Op::Read, 2),
+                (Op::Write, 1),
+                (Op::Flush, 0),
+                (Op::WriteFua, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_queue() {
+        let device: SharedBlockDevice = Arc::new(Mutex::new(RamBlockDevice::new(
+            String::from("mq-t"),
+            512,
+            8,
+        )));
+        let queue = BlockQueue::new(device);
+        assert_eq!(queue.nr_hw_queues(), 1);
+        assert_eq!(queue.scheduler(), "deadline");
+
+        queue.execute(Op::Write, 2, vec![0xAB; 1024]).unwrap();
+        queue.execute(Op::Flush, 0, Vec::new()).unwrap();
+        assert_eq!(
+            queue.execute(Op::Read, 3, vec![0; 512]).unwrap(),
+            [0xAB; 512]
+        );
+
+        queue.set_scheduler("none").unwrap();
+        assert_eq!(queue.scheduler(), "none");
+        assert!(queue.set_scheduler("cfq").is_err());
+        assert!(queue.execute(Op::Read, 8, vec![0; 512]).is_err());
+        assert!(queue.execute(Op::Write, 0, vec![0; 100]).is_err());
+    }
+}
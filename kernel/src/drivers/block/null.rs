@@ -0,0 +1,131 @@
+//! Null Block Device
+//!
+//! `nullbN` accepts every in-range request and completes it at once: reads
+//! leave the buffer untouched and writes are dropped. What a benchmark
+//! against it measures is the cost of the block layer and its callers.
+//!
+//! Command line: `null_blk.nr_devices=` (default 1), `null_blk.gb=`
+//! (capacity of each device, default 250) and `null_blk.submit_queues=`
+//! (hardware queues of each device, default 1).
+
+use alloc::{string::String, sync::Arc};
+
+use spin::Mutex;
+
+use crate::{error::KernelError, fs::blockdev::BlockDevice};
+
+/// Block size of the boot-time devices.
+const BLOCK_SIZE: usize = 512;
+
+crate::kernel_param! {
+    /// Number of null block devices created at boot.
+    static NR_DEVICES: u32 = 1, "null_blk.nr_devices";
+}
+
+crate::kernel_param! {
+    /// Capacity of each null block device in GiB.
+    static SIZE_GB: u64 = 250, "null_blk.gb";
+}
+
+crate::kernel_param! {
+    /// Hardware queues of each null block device.
+    static SUBMIT_QUEUES: usize = 1, "null_blk.submit_queues";
+}
+
+/// A block device that does no I/O.
+pub struct NullBlockDevice {
+    name: String,
+    block_size: usize,
+    block_count: u64,
+    hw_queues: usize,
+}
+
+impl NullBlockDevice {
+    /// Create a device of `block_count` blocks of `block_size` bytes.
+    pub fn new(name: String, block_size: usize, block_count: u64) -> Self {
+        Self {
+            name,
+            block_size,
+            block_count,
+            hw_queues: 1,
+        }
+    }
+
+    /// Offer `hw_queues` hardware queues to the block layer.
+    pub fn with_hw_queues(mut self, hw_queues: usize) -> Self {
+        self.hw_queues = hw_queues.max(1);
+        self
+    }
+
+    fn check_range(&self, start_block: u64, len: usize) -> Result<(), KernelError> {
+        let blocks = (len / self.block_size) as u64;
+        if !len.is_multiple_of(self.block_size)
+            || start_block
+                .checked_add(blocks)
+                .is_none_or(|end| end > self.block_count)
+        {
+            return Err(KernelError::InvalidArgument {
+                name: "block_range",
+                value: "out_of_bounds",
+            });
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for NullBlockDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn block_count(&self) -> u64 {
+        self.block_count
+    }
+
+    fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<(), KernelError> {
+        self.check_range(start_block, buffer.len())
+    }
+
+    fn write_blocks(&mut self, start_block: u64, buffer: &[u8]) -> Result<(), KernelError> {
+        self.check_range(start_block, buffer.len())
+    }
+
+    fn hw_queues(&self) -> usize {
+        self.hw_queues
+    }
+}
+
+/// Create the `null_blk.nr_devices` boot-time devices.
+pub(super) fn init() {
+    let block_count = SIZE_GB.get().saturating_mul(1 << 30) / BLOCK_SIZE as u64;
+    for _ in 0..NR_DEVICES.get() {
+        let name = super::next_free_name("nullb");
+        let device =
+            NullBlockDevice::new(name, BLOCK_SIZE, block_count).with_hw_queues(SUBMIT_QUEUES.get());
+        if let Err(_e) = super::register(Arc::new(Mutex::new(device))) {
+            crate::println!("[NULLB] Failed to register null block device: {:?}", _e);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_device_range() {
+        let mut dev = NullBlockDevice::new(String::from("nullb-t"), 512, 4);
+        let mut buf = [0x5Au8; 1024];
+        assert!(dev.read_blocks(2, &mut buf).is_ok());
+        assert_eq!(buf, [0x5Au8; 1024]);
+        assert!(dev.write_blocks(0, &buf).is_ok());
+        assert!(dev.read_blocks(3, &mut buf).is_err());
+        assert!(dev.write_blocks(u64::MAX, &buf).is_err());
+        assert!(dev.read_blocks(0, &mut buf[..100]).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+//! RAM Disk
+//!
+//! `ramN` is a [`RamBlockDevice`]: memory is only allocated for blocks that
+//! have been written, so an unused disk costs nothing. Useful as scratch
+//! storage in early boot and as a fast, throwaway disk in tests.
+//!
+//! Command line: `brd.rd_nr=` (disks created at boot, default 1) and
+//! `brd.rd_size=` (size of each in KiB, default 4096).
+
+use alloc::{string::String, sync::Arc};
+
+use spin::Mutex;
+
+use crate::{error::KernelError, fs::blockdev::RamBlockDevice};
+
+/// Block size of RAM disks.
+pub const BLOCK_SIZE: usize = 512;
+
+crate::kernel_param! {
+    /// Number of RAM disks created at boot.
+    static RD_NR: u32 = 1, "brd.rd_nr";
+}
+
+crate::kernel_param! {
+    /// Size of each boot-time RAM disk in KiB.
+    static RD_SIZE_KIB: u64 = 4096, "brd.rd_size";
+}
+
+/// Create a RAM disk of `size_kib` KiB and return its name.
+pub fn create(size_kib: u64) -> Result<String, KernelError> {
+    let block_count = size_kib.saturating_mul(1024) / BLOCK_SIZE as u64;
+    if block_count == 0 {
+        return Err(KernelError::InvalidArgument {
+            name: "size_kib",
+            value: "smaller than one block",
+        });
+    }
+    let name = super::next_free_name("ram");
+    let device = RamBlockDevice::new(name.clone(), BLOCK_SIZE, block_count);
+    super::register(Arc::new(Mutex::new(device)))?;
+    Ok(name)
+}
+
+/// Create the `brd.rd_nr` boot-time RAM disks.
+pub(super) fn init() {
+    for _ in 0..RD_NR.get() {
+        if let Err(_e) = create(RD_SIZE_KIB.get()) {
+            crate::println!("[RAMDISK] Failed to create RAM disk: {:?}", _e);
+            return;
+        }
+    }
+}
@@ -584,12 +584,12 @@ impl Driver for ConsoleDriver {
         }
     }
 
-    fn suspend(&mut self) -> Result<(), KernelError> {
+    fn suspend(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         crate::println!("[CONSOLE] Suspending console driver");
         Ok(())
     }
 
-    fn resume(&mut self) -> Result<(), KernelError> {
+    fn resume(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         crate::println!("[CONSOLE] Resuming console driver");
         Ok(())
     }
@@ -0,0 +1,105 @@
+//! Shared helpers for device drivers.
+//!
+//! [`DriverCell`] holds a driver's global state. State that an interrupt
+//! handler touches cannot sit behind a plain spinlock: if the IRQ arrives
+//! while the same CPU holds the lock, the handler spins forever. The cell
+//! masks interrupts on the local CPU for as long as the lock is held, so
+//! the lock is only ever contended across CPUs.
+
+use core::ops::{Deref, DerefMut};
+
+use spin::{Mutex, MutexGuard};
+
+/// Global driver state shared between interrupt handlers and normal code.
+///
+/// Interrupts are disabled while the state is borrowed and restored to
+/// their previous state afterwards, so cells may be nested.
+pub struct DriverCell<T> {
+    inner: Mutex<T>,
+}
+
+impl<T> DriverCell<T> {
+    /// Create a cell holding `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            inner: Mutex::new(value),
+        }
+    }
+
+    /// Borrow the state with interrupts masked until the guard is dropped.
+    pub fn lock(&self) -> DriverGuard<'_, T, impl Drop> {
+        let irq = crate::arch::disable_interrupts();
+        DriverGuard {
+            guard: self.inner.lock(),
+            _irq: irq,
+        }
+    }
+
+    /// Run `f` on the state with interrupts masked.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.lock())
+    }
+}
+
+impl<T> DriverCell<Option<T>> {
+    /// Install the driver, replacing and returning any previous one.
+    pub fn set(&self, value: T) -> Option<T> {
+        self.lock().replace(value)
+    }
+
+    /// Whether a driver has been installed.
+    pub fn is_set(&self) -> bool {
+        self.lock().is_some()
+    }
+}
+
+/// Borrow of a [`DriverCell`]; interrupts stay masked until it is dropped.
+pub struct DriverGuard<'a, T, I> {
+    // Fields drop in declaration order: the lock is released before
+    // interrupts are re-enabled.
+    guard: MutexGuard<'a, T>,
+    _irq: I,
+}
+
+impl<T, I> Deref for DriverGuard<'_, T, I> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T, I> DerefMut for DriverGuard<'_, T, I> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_driver_cell_with() {
+        let cell = DriverCell::new(1u32);
+        assert_eq!(cell.with(|v| core::mem::replace(v, 2)), 1);
+        assert_eq!(*cell.lock(), 2);
+    }
+
+    #[test]
+    fn test_driver_cell_set() {
+        let cell: DriverCell<Option<u32>> = DriverCell::new(None);
+        assert!(!cell.is_set());
+        assert_eq!(cell.set(7), None);
+        assert_eq!(cell.set(8), Some(7));
+        assert_eq!(cell.lock().as_mut().map(|v| *v), Some(8));
+    }
+
+    #[test]
+    fn test_driver_cell_nested() {
+        let outer = DriverCell::new(0u8);
+        let inner = DriverCell::new(0u8);
+        outer.with(|a| inner.with(|b| *b = *a + 1));
+        assert_eq!(*inner.lock(), 1);
+    }
+}
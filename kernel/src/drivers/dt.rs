@@ -0,0 +1,305 @@
+//! Device tree device discovery and driver matching
+//!
+//! On AArch64 and RISC-V the hardware is described by the device tree the
+//! firmware passes at boot, saved by [`crate::utils::fdt::init`]. This module
+//! turns the enabled nodes with a known `compatible` string into devices on a
+//! `"dt"` bus, so the driver framework binds drivers to them like it does for
+//! PCI, and answers the MMIO base lookups drivers used to hard-code for
+//! QEMU's `virt` machine.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    error::KernelError,
+    services::driver_framework::{Bus, DeviceClass, DeviceInfo, DeviceStatus, PowerState},
+    utils::fdt::{Fdt, Node},
+};
+
+/// A compatible string the kernel has a driver for.
+struct DtMatch {
+    compatible: &'static str,
+    class: DeviceClass,
+}
+
+/// Compatible strings matched onto the `"dt"` bus, in priority order.
+const MATCHES: &[DtMatch] = &[
+    DtMatch {
+        compatible: "virtio,mmio",
+        class: DeviceClass::Other,
+    },
+    DtMatch {
+        compatible: "arm,pl011",
+        class: DeviceClass::Serial,
+    },
+    DtMatch {
+        compatible: "ns16550a",
+        class: DeviceClass::Serial,
+    },
+    DtMatch {
+        compatible: "ns16550",
+        class: DeviceClass::Serial,
+    },
+];
+
+/// A device found in the device tree.
+#[derive(Debug, Clone)]
+pub struct DtDevice {
+    /// Node name, e.g. `virtio_mmio@a000000`.
+    pub name: String,
+    /// The compatible string it was matched on.
+    pub compatible: &'static str,
+    /// Driver framework class for the match.
+    pub class: DeviceClass,
+    /// `(address, size)` MMIO regions from `reg`.
+    pub regs: Vec<(u64, u64)>,
+    /// Interrupt numbers as seen by the interrupt controller driver.
+    pub irqs: Vec<u32>,
+}
+
+/// Interrupt numbers of `node`, decoded for its interrupt controller.
+///
+/// A GIC uses three cells (type, number, flags) with SPIs numbered from 32
+/// and PPIs from 16; other controllers (the RISC-V PLIC) take the first
+/// cell as the interrupt number.
+fn decode_irqs(fdt: &Fdt<'_>, node: &Node<'_>) -> Vec<u32> {
+    let controller = node
+        .interrupt_parent()
+        .and_then(|phandle| fdt.node_by_phandle(phandle));
+    let cells = controller
+        .and_then(|c| c.u32_property("#interrupt-cells"))
+        .unwrap_or(1)
+        .max(1) as usize;
+    let is_gic = controller.is_some_and(|c| c.compatible().any(|s| s.contains("gic")));
+
+    let raw: Vec<u32> = node.interrupts().collect();
+    raw.chunks_exact(cells)
+        .map(|spec| match (is_gic, spec) {
+            (true, [0, number, ..]) => number + 32,
+            (true, [1, number, ..]) => number + 16,
+            (_, [number, ..]) => *number,
+            (_, []) => 0,
+        })
+        .collect()
+}
+
+fn device_from_node(fdt: &Fdt<'_>, node: &Node<'_>, matched: &DtMatch) -> DtDevice {
+    DtDevice {
+        name: String::from(node.name()),
+        compatible: matched.compatible,
+        class: matched.class,
+        regs: node.reg().collect(),
+        irqs: decode_irqs(fdt, node),
+    }
+}
+
+/// All enabled device tree nodes the kernel has a driver for.
+///
+/// Empty when no device tree was passed at boot.
+pub fn devices() -> Vec<DtDevice> {
+    crate::utils::fdt::with_blob(|fdt| {
+        fdt.nodes()
+            .filter(|node| node.is_enabled())
+            .filter_map(|node| {
+                let matched = MATCHES.iter().find(|m| node.is_compatible(m.compatible))?;
+                Some(device_from_node(&fdt, &node, matched))
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// An enabled device tree node with registers, whatever it is compatible
+/// with.
+#[derive(Debug, Clone)]
+pub struct DtNode {
+    /// Node name, e.g. `virtio_mmio@a000000`.
+    pub name: String,
+    /// Its compatible strings, most specific first.
+    pub compatible: Vec<String>,
+    /// `(address, size)` MMIO regions from `reg`.
+    pub regs: Vec<(u64, u64)>,
+    /// Interrupt numbers as seen by the interrupt controller driver.
+    pub irqs: Vec<u32>,
+}
+
+/// All enabled nodes with a `compatible` and a `reg` property, for the
+/// device manager to match against driver manifests.
+pub fn nodes() -> Vec<DtNode> {
+    crate::utils::fdt::with_blob(|fdt| {
+        fdt.nodes()
+            .filter(|node| node.is_enabled())
+            .filter_map(|node| {
+                let compatible: Vec<String> = node.compatible().map(String::from).collect();
+                let regs: Vec<(u64, u64)> = node.reg().collect();
+                if compatible.is_empty() || regs.is_empty() {
+                    return None;
+                }
+                Some(DtNode {
+                    name: String::from(node.name()),
+                    compatible,
+                    regs,
+                    irqs: decode_irqs(&fdt, &node),
+                })
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Enabled nodes compatible with `compatible`, whether or not the kernel
+/// has a driver for them.
+pub fn find_compatible(compatible: &str) -> Vec<(String, Vec<(u64, u64)>)> {
+    crate::utils::fdt::with_blob(|fdt| {
+        fdt.compatible_nodes(compatible)
+            .map(|node| (String::from(node.name()), node.reg().collect()))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// First MMIO address of each enabled node compatible with `compatible`, in
+/// ascending order.
+pub fn mmio_bases(compatible: &str) -> Vec<usize> {
+    let mut bases: Vec<usize> = find_compatible(compatible)
+        .into_iter()
+        .filter_map(|(_, regs)| regs.first().map(|&(address, _)| address as usize))
+        .collect();
+    bases.sort_unstable();
+    bases
+}
+
+/// MMIO base of the console UART: the `/chosen/stdout-path` node if it
+/// names one of the matched serial devices, else the first of them.
+pub fn console_uart() -> Option<(usize, &'static str)> {
+    crate::utils::fdt::with_blob(|fdt| {
+        let stdout = fdt
+            .chosen()
+            .and_then(|chosen| chosen.str_property("stdout-path"))
+            // "/pl011@9000000:115200n8" -> "pl011@9000000"
+            .filter(|path| path.starts_with('/'))
+            .map(|path| {
+                let path = path.split(':').next().unwrap_or(path);
+                path.rsplit('/').next().unwrap_or(path)
+            });
+
+        let mut first = None;
+        for node in fdt.nodes().filter(|node| node.is_enabled()) {
+            let Some(matched) = MATCHES
+                .iter()
+                .filter(|m| m.class == DeviceClass::Serial)
+                .find(|m| node.is_compatible(m.compatible))
+            else {
+                continue;
+            };
+            let Some((address, _)) = node.reg().next() else {
+                continue;
+            };
+            let uart = (address as usize, matched.compatible);
+            if stdout == Some(node.name()) {
+                return Some(uart);
+            }
+            first = first.or(Some(uart));
+        }
+        first
+    })
+    .flatten()
+}
+
+/// The device tree as a driver framework bus.
+pub struct DeviceTreeBus;
+
+impl Bus for DeviceTreeBus {
+    fn name(&self) -> &str {
+        "dt"
+    }
+
+    fn scan(&mut self) -> Vec<DeviceInfo> {
+        devices()
+            .into_iter()
+            .map(|dev| DeviceInfo {
+                id: 0,
+                name: format!("{} ({})", dev.name, dev.compatible),
+                class: dev.class,
+                device_id: None,
+                driver: None,
+                bus: String::from("dt"),
+                address: dev.regs.first().map_or(0, |&(address, _)| address),
+                irq: dev.irqs.first().and_then(|&irq| u8::try_from(irq).ok()),
+                dma_channels: Vec::new(),
+                io_ports: Vec::new(),
+                memory_regions: dev.regs,
+                status: DeviceStatus::Uninitialized,
+                parent: None,
+                power: PowerState::On,
+            })
+            .collect()
+    }
+
+    fn read_config(
+        &self,
+        _device: &DeviceInfo,
+        _offset: u16,
+        _size: u8,
+    ) -> Result<u32, KernelError> {
+        Err(KernelError::OperationNotSupported {
+            operation: "config space on device tree bus",
+        })
+    }
+
+    fn write_config(
+        &mut self,
+        _device: &DeviceInfo,
+        _offset: u16,
+        _value: u32,
+        _size: u8,
+    ) -> Result<(), KernelError> {
+        Err(KernelError::OperationNotSupported {
+            operation: "config space on device tree bus",
+        })
+    }
+
+    fn enable_device(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    fn disable_device(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+        Ok(())
+    }
+}
+
+/// Register the device tree bus and point the console at the UART it
+/// describes.
+///
+/// Without a device tree the built-in QEMU `virt` addresses stay in use.
+pub fn init() {
+    if crate::utils::fdt::with_blob(|_| ()).is_none() {
+        crate::println!("[DT] No device tree, using built-in device addresses");
+        return;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if let Some((base, "arm,pl011")) = console_uart() {
+        crate::arch::aarch64::serial::set_base(base);
+    }
+
+    let devices = devices();
+    for dev in &devices {
+        crate::println!(
+            "[DT] {} ({}) regs={:x?} irqs={:?}",
+            dev.name,
+            dev.compatible,
+            dev.regs,
+            dev.irqs
+        );
+    }
+
+    let driver_framework = crate::services::driver_framework::get_driver_framework();
+    if let Err(_e) = driver_framework.register_bus(alloc::boxed::Box::new(DeviceTreeBus)) {
+        crate::println!("[DT] Failed to register device tree bus: {}", _e);
+    } else {
+        crate::println!(
+            "[DT] Device tree bus registered ({} devices)",
+            devices.len()
+        );
+    }
+}
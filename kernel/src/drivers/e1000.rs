@@ -1,49 +1,181 @@
-//! Intel E1000 (82540EM) Network Driver
+//! Intel 8254x/82574 (e1000/e1000e) Network Driver
 //!
-//! This driver supports the Intel E1000 Gigabit Ethernet controller,
-//! commonly found in QEMU and VirtualBox virtual machines.
+//! This driver supports the Intel PRO/1000 family: the 82540EM that QEMU
+//! emulates by default, its 8254x siblings found in VirtualBox and older
+//! machines, and the PCIe 82574L (QEMU's `e1000e`).
+//!
+//! Descriptor rings and packet buffers live in frame-allocator memory and
+//! are handed to the NIC by physical address. Receive is interrupt driven:
+//! the NIC interrupt (MSI where the function has it, otherwise its INTx line
+//! through the I/O APIC) moves completed frames off the RX ring into a
+//! per-NIC queue, and [`poll`], run from the idle loop, hands them to the
+//! Ethernet layer outside interrupt context. The NIC verifies IPv4 and
+//! TCP/UDP checksums of received frames; frames it flags as bad are dropped.
 
 // Intel E1000 driver
 
+use alloc::{boxed::Box, collections::VecDeque, format, string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{self, AtomicBool, AtomicU64, Ordering};
+
+use spin::{Mutex, RwLock};
+
 use crate::{
+    drivers::pci::{self, PciDevice},
     error::KernelError,
+    mm::{phys_to_virt_addr, FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
     net::{
-        device::{DeviceCapabilities, DeviceState, DeviceStatistics, NetworkDevice},
+        device::{self, DeviceCapabilities, DeviceState, DeviceStatistics, NetworkDevice},
         MacAddress, Packet,
     },
+    sched::workqueue::{self, Work},
 };
 
 /// E1000 PCI vendor and device IDs
 pub const E1000_VENDOR_ID: u16 = 0x8086;
 pub const E1000_DEVICE_ID: u16 = 0x100E;
+pub const E1000E_DEVICE_ID: u16 = 0x10D3;
+
+/// Controller generation, which decides the EEPROM read register layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /// PCI/PCI-X 8254x: EERD address at bit 8, done at bit 4
+    Pci,
+    /// PCIe 82574/82583: EERD address at bit 2, done at bit 1
+    Pcie,
+}
+
+/// Supported Intel device IDs
+const DEVICES: &[(u16, Model)] = &[
+    (0x100E, Model::Pci),  // 82540EM (QEMU default)
+    (0x100F, Model::Pci),  // 82545EM copper (VMware)
+    (0x1011, Model::Pci),  // 82545EM fiber
+    (0x1015, Model::Pci),  // 82540EM LOM
+    (0x1016, Model::Pci),  // 82540EP LOM
+    (0x1017, Model::Pci),  // 82540EP
+    (0x101E, Model::Pci),  // 82540EP LP
+    (0x1026, Model::Pci),  // 82545GM copper
+    (0x1076, Model::Pci),  // 82541GI
+    (0x107C, Model::Pci),  // 82541PI
+    (0x10D3, Model::Pcie), // 82574L (QEMU e1000e)
+    (0x10F6, Model::Pcie), // 82574LA
+    (0x150C, Model::Pcie), // 82583V
+];
+
+/// The controller generation of a supported NIC.
+pub fn model(vendor_id: u16, device_id: u16) -> Option<Model> {
+    if vendor_id != E1000_VENDOR_ID {
+        return None;
+    }
+    DEVICES
+        .iter()
+        .find(|&&(id, _)| id == device_id)
+        .map(|&(_, model)| model)
+}
+
+/// Device IDs this driver binds to (all with vendor [`E1000_VENDOR_ID`]).
+pub fn device_ids() -> impl Iterator<Item = u16> {
+    DEVICES.iter().map(|&(id, _)| id)
+}
 
 /// E1000 register offsets
 const REG_CTRL: usize = 0x0000; // Device Control
-#[allow(dead_code)] // E1000 hardware register per Intel spec
 const REG_STATUS: usize = 0x0008; // Device Status
 const REG_EEPROM: usize = 0x0014; // EEPROM Read
 #[allow(dead_code)] // E1000 hardware register per Intel spec
 const REG_CTRL_EXT: usize = 0x0018; // Extended Device Control
 const REG_ICR: usize = 0x00C0; // Interrupt Cause Read
 const REG_IMS: usize = 0x00D0; // Interrupt Mask Set
+const REG_IMC: usize = 0x00D8; // Interrupt Mask Clear
 const REG_RCTL: usize = 0x0100; // Receive Control
 const REG_TCTL: usize = 0x0400; // Transmit Control
+const REG_TIPG: usize = 0x0410; // Transmit Inter Packet Gap
 const REG_RDBAL: usize = 0x2800; // RX Descriptor Base Low
 const REG_RDBAH: usize = 0x2804; // RX Descriptor Base High
 const REG_RDLEN: usize = 0x2808; // RX Descriptor Length
 const REG_RDH: usize = 0x2810; // RX Descriptor Head
 const REG_RDT: usize = 0x2818; // RX Descriptor Tail
+const REG_RDTR: usize = 0x2820; // RX Delay Timer
 const REG_TDBAL: usize = 0x3800; // TX Descriptor Base Low
 const REG_TDBAH: usize = 0x3804; // TX Descriptor Base High
 const REG_TDLEN: usize = 0x3808; // TX Descriptor Length
 const REG_TDH: usize = 0x3810; // TX Descriptor Head
 const REG_TDT: usize = 0x3818; // TX Descriptor Tail
+const REG_RXCSUM: usize = 0x5000; // RX Checksum Control
 const REG_MTA: usize = 0x5200; // Multicast Table Array
+const REG_RAL0: usize = 0x5400; // Receive Address Low
+const REG_RAH0: usize = 0x5404; // Receive Address High
+
+/// CTRL bits
+const CTRL_ASDE: u32 = 1 << 5; // Auto-Speed Detection Enable
+const CTRL_SLU: u32 = 1 << 6; // Set Link Up
+const CTRL_RST: u32 = 1 << 26; // Device Reset
+
+/// STATUS bits
+const STATUS_LU: u32 = 1 << 1; // Link Up
+
+/// Interrupt cause bits (ICR/IMS/IMC)
+const INT_LSC: u32 = 1 << 2; // Link status change
+const INT_RXDMT0: u32 = 1 << 4; // RX descriptor minimum threshold
+const INT_RXO: u32 = 1 << 6; // RX overrun
+const INT_RXT0: u32 = 1 << 7; // RX timer (frame received)
+const INT_RX: u32 = INT_RXDMT0 | INT_RXO | INT_RXT0;
+
+/// RCTL bits (BSIZE 00 with BSEX clear selects 2048-byte buffers)
+const RCTL_EN: u32 = 1 << 1;
+const RCTL_BAM: u32 = 1 << 15; // Accept broadcast
+const RCTL_SECRC: u32 = 1 << 26; // Strip Ethernet CRC
+
+/// TCTL bits
+const TCTL_EN: u32 = 1 << 1;
+const TCTL_PSP: u32 = 1 << 3; // Pad short packets
+const TCTL_CT: u32 = 0x0F << 4; // Collision threshold
+const TCTL_COLD: u32 = 0x40 << 12; // Collision distance (full duplex)
+
+/// IPGT 10, IPGR1 8, IPGR2 6 (copper, per the 8254x manual)
+const TIPG_DEFAULT: u32 = 10 | (8 << 10) | (6 << 20);
+
+/// RXCSUM bits
+const RXCSUM_IPOFLD: u32 = 1 << 8; // IPv4 header checksum offload
+const RXCSUM_TUOFLD: u32 = 1 << 9; // TCP/UDP checksum offload
+
+/// RAH Address Valid
+const RAH_AV: u32 = 1 << 31;
+
+/// RX descriptor status bits
+const RX_STATUS_DD: u8 = 1 << 0; // Descriptor done
+const RX_STATUS_EOP: u8 = 1 << 1; // End of packet
+const RX_STATUS_IXSM: u8 = 1 << 2; // Ignore checksum indication
+const RX_STATUS_TCPCS: u8 = 1 << 5; // TCP/UDP checksum calculated
+const RX_STATUS_IPCS: u8 = 1 << 6; // IPv4 checksum calculated
+
+/// RX descriptor error bits
+const RX_ERR_FRAME: u8 = (1 << 0) | (1 << 1) | (1 << 2) | (1 << 4) | (1 << 7); // CE SE SEQ CXE RXE
+const RX_ERR_TCPE: u8 = 1 << 5; // TCP/UDP checksum error
+const RX_ERR_IPE: u8 = 1 << 6; // IPv4 checksum error
+
+/// TX descriptor command and status bits
+const TX_CMD_EOP: u8 = 1 << 0;
+const TX_CMD_IFCS: u8 = 1 << 1; // Insert FCS
+const TX_CMD_RS: u8 = 1 << 3; // Report status
+const TX_STATUS_DD: u8 = 1 << 0;
 
 /// Number of RX/TX descriptors
 const NUM_RX_DESC: usize = 32;
 const NUM_TX_DESC: usize = 8;
 
+/// Size of each packet buffer
+const BUFFER_SIZE: usize = 2048;
+
+/// Received frames waiting for [`poll`] per NIC; more are dropped
+const RX_QUEUE_LIMIT: usize = 256;
+
+/// Bounded spins for EEPROM reads and reset completion
+const SPIN_LIMIT: usize = 100_000;
+
+/// IDT vector of the NIC interrupt
+#[cfg(target_arch = "x86_64")]
+pub const NIC_VECTOR: u8 = 51;
+
 /// Receive Descriptor
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -69,57 +201,78 @@ struct TxDescriptor {
     special: u16,
 }
 
-/// E1000 Driver State
-pub struct E1000Driver {
-    mmio_base: usize,
-    mac_address: MacAddress,
-    rx_descriptors: [RxDescriptor; NUM_RX_DESC],
-    tx_descriptors: [TxDescriptor; NUM_TX_DESC],
-    rx_buffers: [[u8; 2048]; NUM_RX_DESC],
-    tx_buffers: [[u8; 2048]; NUM_TX_DESC],
-    rx_current: usize,
-    tx_current: usize,
-    state: DeviceState,
-    stats: DeviceStatistics,
+/// What the NIC's receive checksum offload says about a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RxChecksum {
+    /// Not checked: offload off, or not an IPv4/TCP/UDP frame
+    Unchecked,
+    Good,
+    Bad,
 }
 
-impl E1000Driver {
-    /// Create a new E1000 driver instance
-    pub fn new(mmio_base: usize) -> Result<Self, KernelError> {
-        let mut driver = Self {
-            mmio_base,
-            mac_address: MacAddress::ZERO,
-            rx_descriptors: [RxDescriptor {
-                addr: 0,
-                length: 0,
-                checksum: 0,
-                status: 0,
-                errors: 0,
-                special: 0,
-            }; NUM_RX_DESC],
-            tx_descriptors: [TxDescriptor {
-                addr: 0,
-                length: 0,
-                cso: 0,
-                cmd: 0,
-                status: 0,
-                css: 0,
-                special: 0,
-            }; NUM_TX_DESC],
-            rx_buffers: [[0u8; 2048]; NUM_RX_DESC],
-            tx_buffers: [[0u8; 2048]; NUM_TX_DESC],
-            rx_current: 0,
-            tx_current: 0,
-            state: DeviceState::Down,
-            stats: DeviceStatistics::default(),
-        };
+/// Decode the checksum bits of a completed RX descriptor.
+fn rx_checksum(status: u8, errors: u8) -> RxChecksum {
+    if status & RX_STATUS_IXSM != 0 {
+        return RxChecksum::Unchecked;
+    }
+    let ip_bad = status & RX_STATUS_IPCS != 0 && errors & RX_ERR_IPE != 0;
+    let l4_bad = status & RX_STATUS_TCPCS != 0 && errors & RX_ERR_TCPE != 0;
+    if ip_bad || l4_bad {
+        RxChecksum::Bad
+    } else if status & (RX_STATUS_IPCS | RX_STATUS_TCPCS) != 0 {
+        RxChecksum::Good
+    } else {
+        RxChecksum::Unchecked
+    }
+}
 
-        driver.initialize()?;
-        Ok(driver)
+/// Physically contiguous, zeroed frames the NIC reads or writes.
+struct DmaRegion {
+    frame: FrameNumber,
+    count: usize,
+    phys: u64,
+    virt: usize,
+}
+
+impl DmaRegion {
+    fn new(bytes: usize) -> Result<Self, KernelError> {
+        let count = bytes.div_ceil(FRAME_SIZE);
+        let frame = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(count, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: count * FRAME_SIZE,
+                available: 0,
+            })?;
+        let phys = frame.as_u64() * FRAME_SIZE as u64;
+        let virt = phys_to_virt_addr(phys) as usize;
+        // SAFETY: The frames were just allocated for this region and are
+        // reachable through the kernel direct map.
+        unsafe { core::ptr::write_bytes(virt as *mut u8, 0, count * FRAME_SIZE) };
+        Ok(Self {
+            frame,
+            count,
+            phys,
+            virt,
+        })
+    }
+}
+
+impl Drop for DmaRegion {
+    fn drop(&mut self) {
+        let _ = FRAME_ALLOCATOR.lock().free_frames(self.frame, self.count);
     }
+}
 
+/// E1000 register window
+#[derive(Debug, Clone, Copy)]
+struct Regs {
+    mmio_base: usize,
+}
+
+impl Regs {
     /// Read from MMIO register
-    fn read_reg(&self, offset: usize) -> u32 {
+    fn read(&self, offset: usize) -> u32 {
         // SAFETY: Reading an E1000 MMIO register at mmio_base + offset. The mmio_base
         // is the controller's BAR0 address from PCI configuration. read_volatile
         // prevents the compiler from eliding or reordering this hardware register
@@ -128,150 +281,349 @@ impl E1000Driver {
     }
 
     /// Write to MMIO register
-    fn write_reg(&self, offset: usize, value: u32) {
-        // SAFETY: Writing an E1000 MMIO register. Same invariants as read_reg.
+    fn write(&self, offset: usize, value: u32) {
+        // SAFETY: Writing an E1000 MMIO register. Same invariants as read.
         unsafe {
             core::ptr::write_volatile((self.mmio_base + offset) as *mut u32, value);
         }
     }
+}
 
-    /// Read MAC address from EEPROM
-    fn read_mac_address(&mut self) -> MacAddress {
-        let mut mac = [0u8; 6];
+/// The RX descriptor ring and its buffers.
+struct RxRing {
+    descs: DmaRegion,
+    buffers: DmaRegion,
+    /// Next descriptor the NIC completes
+    next: usize,
+}
 
-        // Read from EEPROM words 0-2
-        for i in 0usize..3 {
-            let word = self.eeprom_read(i as u8);
-            mac[i * 2] = (word & 0xFF) as u8;
-            mac[i * 2 + 1] = (word >> 8) as u8;
+impl RxRing {
+    fn new() -> Result<Self, KernelError> {
+        let ring = Self {
+            descs: DmaRegion::new(NUM_RX_DESC * core::mem::size_of::<RxDescriptor>())?,
+            buffers: DmaRegion::new(NUM_RX_DESC * BUFFER_SIZE)?,
+            next: 0,
+        };
+        for i in 0..NUM_RX_DESC {
+            ring.reset(i);
         }
+        Ok(ring)
+    }
 
-        MacAddress(mac)
+    fn desc(&self, index: usize) -> *mut RxDescriptor {
+        (self.descs.virt as *mut RxDescriptor).wrapping_add(index)
     }
 
-    /// Read from EEPROM
-    fn eeprom_read(&self, addr: u8) -> u16 {
-        self.write_reg(REG_EEPROM, 1 | ((addr as u32) << 8));
+    /// Give descriptor `index` back to the NIC with an empty buffer.
+    fn reset(&self, index: usize) {
+        let desc = RxDescriptor {
+            addr: self.buffers.phys + (index * BUFFER_SIZE) as u64,
+            length: 0,
+            checksum: 0,
+            status: 0,
+            errors: 0,
+            special: 0,
+        };
+        // SAFETY: index < NUM_RX_DESC, so the descriptor lies in the ring.
+        unsafe { core::ptr::write_volatile(self.desc(index), desc) };
+    }
 
-        // Wait for read to complete
-        let mut result: u32;
-        loop {
-            result = self.read_reg(REG_EEPROM);
-            if (result & (1 << 4)) != 0 {
-                break;
-            }
+    /// Take the next completed descriptor, if the NIC has written one back.
+    fn completed(&self) -> Option<RxDescriptor> {
+        // SAFETY: next < NUM_RX_DESC; the NIC writes whole descriptors.
+        let desc = unsafe { core::ptr::read_volatile(self.desc(self.next)) };
+        if desc.status & RX_STATUS_DD == 0 {
+            return None;
         }
+        // Read the buffer only after seeing DD.
+        atomic::fence(Ordering::Acquire);
+        Some(desc)
+    }
 
-        ((result >> 16) & 0xFFFF) as u16
+    fn buffer(&self, index: usize, len: usize) -> &[u8] {
+        // SAFETY: index < NUM_RX_DESC and len <= BUFFER_SIZE, so the slice
+        // lies inside this descriptor's buffer, which the NIC has finished
+        // writing (DD set).
+        unsafe {
+            core::slice::from_raw_parts(
+                (self.buffers.virt + index * BUFFER_SIZE) as *const u8,
+                len.min(BUFFER_SIZE),
+            )
+        }
     }
+}
 
-    /// Initialize the E1000 device
-    fn initialize(&mut self) -> Result<(), KernelError> {
-        // Read MAC address
-        self.mac_address = self.read_mac_address();
+/// The TX descriptor ring and its buffers.
+struct TxRing {
+    descs: DmaRegion,
+    buffers: DmaRegion,
+    /// Next descriptor to fill (mirrors TDT)
+    tail: usize,
+}
 
-        // Enable bus mastering and memory access
-        // (Would normally be done via PCI configuration space)
+impl TxRing {
+    fn new() -> Result<Self, KernelError> {
+        let ring = Self {
+            descs: DmaRegion::new(NUM_TX_DESC * core::mem::size_of::<TxDescriptor>())?,
+            buffers: DmaRegion::new(NUM_TX_DESC * BUFFER_SIZE)?,
+            tail: 0,
+        };
+        for i in 0..NUM_TX_DESC {
+            let desc = TxDescriptor {
+                addr: ring.buffers.phys + (i * BUFFER_SIZE) as u64,
+                length: 0,
+                cso: 0,
+                cmd: 0,
+                // Free until first used
+                status: TX_STATUS_DD,
+                css: 0,
+                special: 0,
+            };
+            // SAFETY: i < NUM_TX_DESC, so the descriptor lies in the ring.
+            unsafe { core::ptr::write_volatile(ring.desc(i), desc) };
+        }
+        Ok(ring)
+    }
 
-        // Reset the device
-        self.write_reg(REG_CTRL, self.read_reg(REG_CTRL) | 0x04000000);
+    fn desc(&self, index: usize) -> *mut TxDescriptor {
+        (self.descs.virt as *mut TxDescriptor).wrapping_add(index)
+    }
 
-        // Wait for reset
-        for _ in 0..1000 {
-            if (self.read_reg(REG_CTRL) & 0x04000000) == 0 {
-                break;
-            }
+    /// Queue `frame` on the tail descriptor; returns the new tail.
+    fn push(&mut self, frame: &[u8]) -> Result<usize, KernelError> {
+        let index = self.tail;
+        // SAFETY: index < NUM_TX_DESC.
+        let mut desc = unsafe { core::ptr::read_volatile(self.desc(index)) };
+        if desc.status & TX_STATUS_DD == 0 {
+            // The NIC has not sent the frame a full ring ago yet.
+            return Err(KernelError::WouldBlock);
         }
+        // SAFETY: The buffer of a descriptor with DD set is not read by the
+        // NIC, and frame.len() <= BUFFER_SIZE was checked by the caller.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                frame.as_ptr(),
+                (self.buffers.virt + index * BUFFER_SIZE) as *mut u8,
+                frame.len(),
+            );
+        }
+        desc.length = frame.len() as u16;
+        desc.cmd = TX_CMD_EOP | TX_CMD_IFCS | TX_CMD_RS;
+        desc.status = 0;
+        // SAFETY: As above.
+        unsafe { core::ptr::write_volatile(self.desc(index), desc) };
+        self.tail = (index + 1) % NUM_TX_DESC;
+        Ok(self.tail)
+    }
+}
 
-        // Disable interrupts
-        self.write_reg(REG_IMS, 0);
-        self.read_reg(REG_ICR); // Clear pending interrupts
+/// Receive-side state of a NIC, shared with the interrupt handler.
+struct Nic {
+    name: String,
+    regs: Regs,
+    mac_address: MacAddress,
+    rx: Mutex<RxRing>,
+    /// Received frames not yet handed to the stack
+    queue: Mutex<VecDeque<Vec<u8>>>,
+    link_up: AtomicBool,
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+    rx_errors: AtomicU64,
+    rx_dropped: AtomicU64,
+}
 
-        // Initialize RX descriptors
-        for i in 0..NUM_RX_DESC {
-            self.rx_descriptors[i].addr = &self.rx_buffers[i] as *const _ as u64;
-            self.rx_descriptors[i].status = 0;
+impl Nic {
+    /// Move completed RX descriptors into the queue and recycle them.
+    ///
+    /// Runs in interrupt context as well as from [`poll`], so it never
+    /// waits for a lock: whatever it cannot take now is left for the next
+    /// call.
+    fn service(&self) {
+        let Some(mut rx) = self.rx.try_lock() else {
+            return;
+        };
+        let Some(mut queue) = self.queue.try_lock() else {
+            return;
+        };
+        while let Some(desc) = rx.completed() {
+            let index = rx.next;
+            let len = desc.length as usize;
+            if desc.errors & RX_ERR_FRAME != 0 || desc.status & RX_STATUS_EOP == 0 {
+                // Bad frame, or one longer than a buffer (long packets are
+                // not enabled, so the NIC should not produce these).
+                self.rx_errors.fetch_add(1, Ordering::Relaxed);
+            } else if rx_checksum(desc.status, desc.errors) == RxChecksum::Bad {
+                self.rx_errors.fetch_add(1, Ordering::Relaxed);
+            } else if queue.len() >= RX_QUEUE_LIMIT {
+                self.rx_dropped.fetch_add(1, Ordering::Relaxed);
+            } else {
+                queue.push_back(rx.buffer(index, len).to_vec());
+                self.rx_packets.fetch_add(1, Ordering::Relaxed);
+                self.rx_bytes.fetch_add(len as u64, Ordering::Relaxed);
+            }
+            rx.reset(index);
+            atomic::fence(Ordering::Release);
+            // The descriptor just recycled becomes the last one the NIC owns.
+            self.regs.write(REG_RDT, index as u32);
+            rx.next = (index + 1) % NUM_RX_DESC;
         }
+    }
 
-        // Initialize TX descriptors
-        for i in 0..NUM_TX_DESC {
-            self.tx_descriptors[i].addr = &self.tx_buffers[i] as *const _ as u64;
-            self.tx_descriptors[i].status = 1; // DD bit set
-            self.tx_descriptors[i].cmd = 0;
+    /// Acknowledge and handle the NIC's pending interrupt causes.
+    fn handle_interrupt(&self) {
+        // Reading ICR clears it and deasserts the interrupt.
+        let icr = self.regs.read(REG_ICR);
+        if icr & INT_LSC != 0 {
+            self.update_link();
         }
+        if icr & INT_RX != 0 {
+            self.service();
+        }
+    }
 
-        // Set up RX ring
-        let rx_desc_addr = &self.rx_descriptors as *const _ as u64;
-        self.write_reg(REG_RDBAL, (rx_desc_addr & 0xFFFFFFFF) as u32);
-        self.write_reg(REG_RDBAH, (rx_desc_addr >> 32) as u32);
-        self.write_reg(REG_RDLEN, (NUM_RX_DESC * 16) as u32);
-        self.write_reg(REG_RDH, 0);
-        self.write_reg(REG_RDT, (NUM_RX_DESC - 1) as u32);
+    fn update_link(&self) {
+        let up = self.regs.read(REG_STATUS) & STATUS_LU != 0;
+        self.link_up.store(up, Ordering::Relaxed);
+    }
 
-        // Set up TX ring
-        let tx_desc_addr = &self.tx_descriptors as *const _ as u64;
-        self.write_reg(REG_TDBAL, (tx_desc_addr & 0xFFFFFFFF) as u32);
-        self.write_reg(REG_TDBAH, (tx_desc_addr >> 32) as u32);
-        self.write_reg(REG_TDLEN, (NUM_TX_DESC * 16) as u32);
-        self.write_reg(REG_TDH, 0);
-        self.write_reg(REG_TDT, 0);
+    /// Unmask the receive and link-change interrupts, once the NIC's
+    /// interrupt is routed to [`handle_interrupt`].
+    fn enable_interrupts(&self) {
+        self.regs.read(REG_ICR);
+        self.regs.write(REG_IMS, INT_RX | INT_LSC);
+    }
+
+    fn pop(&self) -> Option<Vec<u8>> {
+        self.queue.try_lock()?.pop_front()
+    }
+}
+
+/// E1000 Driver State
+pub struct E1000Driver {
+    nic: Arc<Nic>,
+    model: Model,
+    tx: TxRing,
+    state: DeviceState,
+    stats: DeviceStatistics,
+}
+
+impl E1000Driver {
+    /// Reset and bring up the controller whose BAR0 is mapped at
+    /// `mmio_base`, naming its interface `name`.
+    ///
+    /// Interrupts stay masked; [`probe`] unmasks them once they are routed.
+    pub fn new(mmio_base: usize, model: Model, name: String) -> Result<Self, KernelError> {
+        let regs = Regs { mmio_base };
+        reset(regs)?;
+        let mac_address = read_mac_address(regs, model);
+
+        let nic = Arc::new(Nic {
+            name,
+            regs,
+            mac_address,
+            rx: Mutex::new(RxRing::new()?),
+            queue: Mutex::new(VecDeque::new()),
+            link_up: AtomicBool::new(false),
+            rx_packets: AtomicU64::new(0),
+            rx_bytes: AtomicU64::new(0),
+            rx_errors: AtomicU64::new(0),
+            rx_dropped: AtomicU64::new(0),
+        });
+        let mut driver = Self {
+            nic,
+            model,
+            tx: TxRing::new()?,
+            state: DeviceState::Down,
+            stats: DeviceStatistics::default(),
+        };
+        driver.initialize();
+        Ok(driver)
+    }
 
-        // Enable receiver
-        self.write_reg(REG_RCTL, (1 << 1) | (1 << 2) | (1 << 15));
+    /// Program the MAC filter and the RX/TX rings, then enable both.
+    fn initialize(&mut self) {
+        let regs = self.nic.regs;
+        let mac = self.nic.mac_address.0;
 
-        // Enable transmitter
-        self.write_reg(REG_TCTL, (1 << 1) | (1 << 3) | (0x10 << 4));
+        // Bring the link up with autonegotiated speed
+        regs.write(REG_CTRL, regs.read(REG_CTRL) | CTRL_SLU | CTRL_ASDE);
+
+        // Receive address 0 is our MAC
+        regs.write(
+            REG_RAL0,
+            u32::from_le_bytes([mac[0], mac[1], mac[2], mac[3]]),
+        );
+        regs.write(
+            REG_RAH0,
+            u32::from_le_bytes([mac[4], mac[5], 0, 0]) | RAH_AV,
+        );
 
         // Clear multicast table
         for i in 0..128 {
-            self.write_reg(REG_MTA + i * 4, 0);
+            regs.write(REG_MTA + i * 4, 0);
         }
 
+        // Set up RX ring
+        let rx_phys = self.nic.rx.lock().descs.phys;
+        regs.write(REG_RDBAL, (rx_phys & 0xFFFFFFFF) as u32);
+        regs.write(REG_RDBAH, (rx_phys >> 32) as u32);
+        regs.write(REG_RDLEN, (NUM_RX_DESC * 16) as u32);
+        regs.write(REG_RDH, 0);
+        regs.write(REG_RDT, (NUM_RX_DESC - 1) as u32);
+        // Interrupt per frame, no delay
+        regs.write(REG_RDTR, 0);
+        regs.write(
+            REG_RXCSUM,
+            regs.read(REG_RXCSUM) | RXCSUM_IPOFLD | RXCSUM_TUOFLD,
+        );
+
+        // Set up TX ring
+        let tx_phys = self.tx.descs.phys;
+        regs.write(REG_TDBAL, (tx_phys & 0xFFFFFFFF) as u32);
+        regs.write(REG_TDBAH, (tx_phys >> 32) as u32);
+        regs.write(REG_TDLEN, (NUM_TX_DESC * 16) as u32);
+        regs.write(REG_TDH, 0);
+        regs.write(REG_TDT, 0);
+        regs.write(REG_TIPG, TIPG_DEFAULT);
+
+        self.enable_rx_tx();
+        self.nic.update_link();
+
         println!(
-            "[E1000] Initialized with MAC: {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
-            self.mac_address.0[0],
-            self.mac_address.0[1],
-            self.mac_address.0[2],
-            self.mac_address.0[3],
-            self.mac_address.0[4],
-            self.mac_address.0[5]
+            "[E1000] {} ({:?}) initialized with MAC: {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            self.nic.name, self.model, mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]
         );
 
         // Device is now up
         self.state = DeviceState::Up;
+    }
 
-        Ok(())
+    fn enable_rx_tx(&self) {
+        let regs = self.nic.regs;
+        regs.write(REG_RCTL, RCTL_EN | RCTL_BAM | RCTL_SECRC);
+        regs.write(REG_TCTL, TCTL_EN | TCTL_PSP | TCTL_CT | TCTL_COLD);
     }
 
     /// Transmit a packet (raw implementation)
     fn transmit_raw(&mut self, packet: &[u8]) -> Result<(), KernelError> {
-        if packet.len() > 2048 {
+        if packet.len() > BUFFER_SIZE {
             return Err(KernelError::InvalidArgument {
                 name: "packet_size",
                 value: "too_large",
             });
         }
 
-        let idx = self.tx_current;
-        let desc = &mut self.tx_descriptors[idx];
-
-        // Wait for descriptor to be available
-        if (desc.status & 1) == 0 {
-            self.stats.tx_dropped += 1;
-            return Err(KernelError::WouldBlock);
-        }
-
-        // Copy packet to TX buffer
-        self.tx_buffers[idx][..packet.len()].copy_from_slice(packet);
-
-        // Set up descriptor
-        desc.length = packet.len() as u16;
-        desc.cmd = (1 << 0) | (1 << 1) | (1 << 3); // EOP | IFCS | RS
-        desc.status = 0;
-
-        // Update tail pointer
-        self.tx_current = (self.tx_current + 1) % NUM_TX_DESC;
-        self.write_reg(REG_TDT, self.tx_current as u32);
+        let tail = match self.tx.push(packet) {
+            Ok(tail) => tail,
+            Err(e) => {
+                self.stats.tx_dropped += 1;
+                return Err(e);
+            }
+        };
+        // Descriptor and buffer writes must reach memory before the NIC
+        // sees the new tail.
+        atomic::fence(Ordering::SeqCst);
+        self.nic.regs.write(REG_TDT, tail as u32);
 
         // Update statistics
         self.stats.tx_packets += 1;
@@ -280,74 +632,109 @@ impl E1000Driver {
         Ok(())
     }
 
-    /// Receive a packet (raw implementation)
-    fn receive_raw(&mut self) -> Result<Option<Packet>, KernelError> {
-        let idx = self.rx_current;
-        let desc = &mut self.rx_descriptors[idx];
+    /// Get MAC address
+    pub fn mac_address(&self) -> MacAddress {
+        self.nic.mac_address
+    }
+}
 
-        // Check if packet is available
-        if (desc.status & 1) == 0 {
-            return Ok(None);
-        }
+impl Drop for E1000Driver {
+    fn drop(&mut self) {
+        // Stop DMA before the rings are freed.
+        let regs = self.nic.regs;
+        regs.write(REG_IMC, u32::MAX);
+        regs.write(REG_RCTL, 0);
+        regs.write(REG_TCTL, 0);
+    }
+}
 
-        // Check for errors
-        if desc.errors != 0 {
-            self.stats.rx_errors += 1;
-            // Reset descriptor
-            desc.status = 0;
-            self.rx_current = (self.rx_current + 1) % NUM_RX_DESC;
-            self.write_reg(REG_RDT, self.rx_current as u32);
-            return Ok(None);
+/// Reset the controller and leave its interrupts masked.
+fn reset(regs: Regs) -> Result<(), KernelError> {
+    regs.write(REG_IMC, u32::MAX);
+    regs.write(REG_CTRL, regs.read(REG_CTRL) | CTRL_RST);
+    let mut spins = 0;
+    while regs.read(REG_CTRL) & CTRL_RST != 0 {
+        spins += 1;
+        if spins == SPIN_LIMIT {
+            return Err(KernelError::Timeout {
+                operation: "e1000 reset",
+                duration_ms: 0,
+            });
         }
+        core::hint::spin_loop();
+    }
+    // Reset re-enables nothing, but clear anything latched before it.
+    regs.write(REG_IMC, u32::MAX);
+    regs.read(REG_ICR);
+    Ok(())
+}
 
-        // Get packet data
-        let len = desc.length as usize;
-        let data = self.rx_buffers[idx][..len].to_vec();
-        let packet = Packet::from_bytes(&data);
-
-        // Update statistics
-        self.stats.rx_packets += 1;
-        self.stats.rx_bytes += len as u64;
-
-        // Reset descriptor
-        desc.status = 0;
-
-        // Update tail pointer
-        self.rx_current = (self.rx_current + 1) % NUM_RX_DESC;
-        self.write_reg(REG_RDT, self.rx_current as u32);
-
-        Ok(Some(packet))
+/// Read an EEPROM word, or `None` if the controller has no EEPROM (or
+/// never completes the read).
+fn eeprom_read(regs: Regs, model: Model, addr: u8) -> Option<u16> {
+    let (addr_shift, done) = match model {
+        Model::Pci => (8, 1 << 4),
+        Model::Pcie => (2, 1 << 1),
+    };
+    regs.write(REG_EEPROM, 1 | ((addr as u32) << addr_shift));
+    for _ in 0..SPIN_LIMIT {
+        let result = regs.read(REG_EEPROM);
+        if result & done != 0 {
+            return Some((result >> 16) as u16);
+        }
+        core::hint::spin_loop();
     }
+    None
+}
 
-    /// Get MAC address
-    pub fn mac_address(&self) -> MacAddress {
-        self.mac_address
+/// Read the MAC address from EEPROM words 0-2, falling back to receive
+/// address 0, which firmware (and QEMU) preload.
+fn read_mac_address(regs: Regs, model: Model) -> MacAddress {
+    let mut mac = [0u8; 6];
+    let from_eeprom = (0..3).all(|i| match eeprom_read(regs, model, i) {
+        Some(word) => {
+            mac[i as usize * 2..][..2].copy_from_slice(&word.to_le_bytes());
+            true
+        }
+        None => false,
+    });
+    if !from_eeprom || mac == [0; 6] || mac == [0xFF; 6] {
+        let low = regs.read(REG_RAL0).to_le_bytes();
+        let high = regs.read(REG_RAH0).to_le_bytes();
+        mac = [low[0], low[1], low[2], low[3], high[0], high[1]];
     }
+    MacAddress(mac)
 }
 
 // DeviceDriver trait implementation removed - using NetworkDevice trait instead
 
 impl NetworkDevice for E1000Driver {
     fn name(&self) -> &str {
-        "eth0"
+        &self.nic.name
     }
 
     fn mac_address(&self) -> MacAddress {
-        self.mac_address
+        self.nic.mac_address
     }
 
     fn capabilities(&self) -> DeviceCapabilities {
         DeviceCapabilities {
             max_transmission_unit: 1500,
             supports_vlan: false,
+            // Receive-side IPv4 and TCP/UDP checksum verification
             supports_checksum_offload: true,
             supports_tso: false,
             supports_lro: false,
+            supports_scatter_gather: false,
         }
     }
 
     fn state(&self) -> DeviceState {
-        self.state
+        if self.state == DeviceState::Up && !self.nic.link_up.load(Ordering::Relaxed) {
+            DeviceState::Dormant
+        } else {
+            self.state
+        }
     }
 
     fn set_state(&mut self, state: DeviceState) -> Result<(), KernelError> {
@@ -355,15 +742,14 @@ impl NetworkDevice for E1000Driver {
             DeviceState::Up => {
                 if self.state == DeviceState::Down {
                     // Re-enable RX and TX
-                    self.write_reg(REG_RCTL, (1 << 1) | (1 << 2) | (1 << 15));
-                    self.write_reg(REG_TCTL, (1 << 1) | (1 << 3) | (0x10 << 4));
+                    self.enable_rx_tx();
                 }
                 self.state = DeviceState::Up;
             }
             DeviceState::Down => {
                 // Disable RX and TX
-                self.write_reg(REG_RCTL, 0);
-                self.write_reg(REG_TCTL, 0);
+                self.nic.regs.write(REG_RCTL, 0);
+                self.nic.regs.write(REG_TCTL, 0);
                 self.state = DeviceState::Down;
             }
             _ => {
@@ -374,7 +760,13 @@ impl NetworkDevice for E1000Driver {
     }
 
     fn statistics(&self) -> DeviceStatistics {
-        self.stats
+        DeviceStatistics {
+            rx_packets: self.nic.rx_packets.load(Ordering::Relaxed),
+            rx_bytes: self.nic.rx_bytes.load(Ordering::Relaxed),
+            rx_errors: self.nic.rx_errors.load(Ordering::Relaxed),
+            rx_dropped: self.nic.rx_dropped.load(Ordering::Relaxed),
+            ..self.stats
+        }
     }
 
     fn transmit(&mut self, packet: &Packet) -> Result<(), KernelError> {
@@ -394,7 +786,113 @@ impl NetworkDevice for E1000Driver {
             return Ok(None);
         }
 
-        self.receive_raw()
+        self.nic.service();
+        Ok(self.nic.pop().map(|frame| Packet::from_bytes(&frame)))
+    }
+}
+
+/// Receive sides of the probed NICs, for the interrupt handler and [`poll`]
+static NICS: RwLock<Vec<Arc<Nic>>> = RwLock::new(Vec::new());
+
+/// First `ethN` not yet taken by a network device.
+fn free_name() -> String {
+    let taken = device::list_devices();
+    (0..)
+        .map(|n| format!("eth{}", n))
+        .find(|name| !taken.contains(name))
+        .unwrap_or_default()
+}
+
+/// Bind to the e1000/e1000e function `device`: enable bus mastering,
+/// bring the NIC up, register it with the network stack and wire its
+/// interrupt.
+///
+/// Must not be called with the PCI bus lock held.
+pub fn probe(device: &PciDevice) -> Result<(), KernelError> {
+    let model = model(device.vendor_id, device.device_id).ok_or(KernelError::NotFound {
+        resource: "e1000_hardware",
+        id: device.device_id as u64,
+    })?;
+    let bar0 = device
+        .bars
+        .first()
+        .and_then(|bar| bar.get_memory_address())
+        .ok_or(KernelError::NotFound {
+            resource: "e1000_mmio_bar",
+            id: 0,
+        })?;
+
+    pci::enable_bus_master(device.location);
+    let driver = E1000Driver::new(phys_to_virt_addr(bar0) as usize, model, free_name())?;
+    let nic = driver.nic.clone();
+    device::register_device(Box::new(driver))?;
+    // Known to the handler before anything can interrupt.
+    NICS.write().push(nic.clone());
+
+    if route_interrupt(device) {
+        nic.enable_interrupts();
+    } else {
+        println!("[E1000] No interrupt routed, receiving by polling");
+    }
+    Ok(())
+}
+
+/// Deliver the NIC interrupt to [`NIC_VECTOR`]: by MSI if the function
+/// has it, otherwise through the I/O APIC from its INTx line.
+#[cfg(target_arch = "x86_64")]
+fn route_interrupt(device: &PciDevice) -> bool {
+    use crate::arch::x86_64::apic;
+    let dest = apic::read_id().unwrap_or(0);
+    if let Some(msi) = &device.msi {
+        pci::configure_msi(device.location, msi, NIC_VECTOR, dest);
+        return true;
+    }
+    // 0xFF: no line assigned by firmware
+    device.interrupt_pin != 0
+        && device.interrupt_line != 0xFF
+        && apic::set_irq_route(device.interrupt_line, NIC_VECTOR, dest).is_ok()
+}
+
+/// Deliver the NIC interrupt to [`handle_interrupt`].
+#[cfg(not(target_arch = "x86_64"))]
+fn route_interrupt(_device: &PciDevice) -> bool {
+    false
+}
+
+/// Deferred RX processing, queued by the interrupt handler.
+static RX_WORK: Work = Work::new(rx_work, 0);
+
+fn rx_work(_data: usize) {
+    poll();
+}
+
+/// NIC interrupt: acknowledge each NIC and move received frames off its
+/// ring, then queue [`poll`] as deferred work. Only try-locks, so it is safe
+/// whatever it interrupted; the NICs share one vector.
+pub fn handle_interrupt() {
+    if let Some(nics) = NICS.try_read() {
+        for nic in nics.iter() {
+            nic.handle_interrupt();
+        }
+    }
+    workqueue::queue_work(&RX_WORK);
+}
+
+/// Hand received frames to the Ethernet layer.
+///
+/// Runs as deferred work after each NIC interrupt and from the idle loop
+/// (via `net::poll`). Also drains the RX rings, so frames arrive even where
+/// the interrupt could not be routed.
+pub fn poll() {
+    let nics: Vec<Arc<Nic>> = match NICS.try_read() {
+        Some(nics) => nics.clone(),
+        None => return,
+    };
+    for nic in nics {
+        nic.service();
+        while let Some(frame) = nic.pop() {
+            let _ = crate::net::ethernet::dispatch_frame(&nic.name, &frame, &nic.mac_address);
+        }
     }
 }
 
@@ -415,4 +913,47 @@ mod tests {
         assert_eq!(NUM_RX_DESC, 32);
         assert_eq!(NUM_TX_DESC, 8);
     }
+
+    #[test]
+    fn test_descriptor_layout() {
+        assert_eq!(core::mem::size_of::<RxDescriptor>(), 16);
+        assert_eq!(core::mem::size_of::<TxDescriptor>(), 16);
+        // RDLEN/TDLEN must be multiples of 128 bytes
+        assert_eq!(NUM_RX_DESC * 16 % 128, 0);
+        assert_eq!(NUM_TX_DESC * 16 % 128, 0);
+    }
+
+    #[test]
+    fn test_model() {
+        assert_eq!(model(0x8086, E1000_DEVICE_ID), Some(Model::Pci));
+        assert_eq!(model(0x8086, E1000E_DEVICE_ID), Some(Model::Pcie));
+        assert_eq!(model(0x8086, 0x1234), None);
+        assert_eq!(model(0x1AF4, E1000_DEVICE_ID), None);
+        assert!(device_ids().any(|id| id == 0x100F));
+    }
+
+    #[test]
+    fn test_rx_checksum() {
+        let dd = RX_STATUS_DD | RX_STATUS_EOP;
+        assert_eq!(rx_checksum(dd | RX_STATUS_IXSM, 0), RxChecksum::Unchecked);
+        assert_eq!(rx_checksum(dd, 0), RxChecksum::Unchecked);
+        assert_eq!(rx_checksum(dd | RX_STATUS_IPCS, 0), RxChecksum::Good);
+        assert_eq!(
+            rx_checksum(dd | RX_STATUS_IPCS | RX_STATUS_TCPCS, 0),
+            RxChecksum::Good
+        );
+        assert_eq!(
+            rx_checksum(dd | RX_STATUS_IPCS, RX_ERR_IPE),
+            RxChecksum::Bad
+        );
+        assert_eq!(
+            rx_checksum(dd | RX_STATUS_IPCS | RX_STATUS_TCPCS, RX_ERR_TCPE),
+            RxChecksum::Bad
+        );
+        // An error bit without the matching "calculated" bit is ignored
+        assert_eq!(
+            rx_checksum(dd | RX_STATUS_IPCS, RX_ERR_TCPE),
+            RxChecksum::Good
+        );
+    }
 }
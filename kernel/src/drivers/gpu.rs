@@ -7,8 +7,7 @@
 
 use core::slice;
 
-use spin::Mutex;
-
+use super::driver_common::DriverCell;
 use crate::{
     error::KernelError,
     graphics::{Color, PixelFormat},
@@ -320,8 +319,8 @@ impl GpuDriver {
     }
 }
 
-/// Global GPU driver instance protected by Mutex
-static GPU_DRIVER: Mutex<Option<GpuDriver>> = Mutex::new(None);
+/// Global GPU driver instance
+static GPU_DRIVER: DriverCell<Option<GpuDriver>> = DriverCell::new(None);
 
 /// Initialize GPU driver
 pub fn init() -> Result<(), KernelError> {
@@ -331,7 +330,7 @@ pub fn init() -> Result<(), KernelError> {
 
     let (width, height, mode) = (driver.width, driver.height, driver.detection_mode());
 
-    *GPU_DRIVER.lock() = Some(driver);
+    GPU_DRIVER.set(driver);
 
     println!(
         "[GPU] GPU driver initialized ({}x{}, {})",
@@ -3,19 +3,40 @@
 //! Collects keyboard and mouse input into a single event stream using
 //! a Linux-compatible event structure. User-space reads events via
 //! `sys_input_read()`.
+//!
+//! Processes holding the input-injection capability (see [`grant_inject`])
+//! can feed synthetic events in with [`inject`], e.g. an on-screen keyboard
+//! or a switch-access helper. Injected keys go through the same sticky keys
+//! handling as typed ones.
 
 use core::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::{
+    cap::{manager::cap_manager, ObjectRef, Rights},
+    error::KernelError,
+    process::ProcessId,
+};
+
 /// Input event types (Linux evdev compatible).
 pub const EV_KEY: u16 = 0x01;
 pub const EV_REL: u16 = 0x02;
-#[allow(dead_code)] // Future: touchscreen, tablet
 pub const EV_ABS: u16 = 0x03;
 
 /// Relative axis codes.
 pub const REL_X: u16 = 0x00;
 pub const REL_Y: u16 = 0x01;
 
+/// Absolute axis codes, in screen pixels.
+pub const ABS_X: u16 = 0x00;
+pub const ABS_Y: u16 = 0x01;
+
+/// Modifier key codes accepted by [`inject`]. They change the keyboard's
+/// modifier state and never appear in the event stream.
+pub const KEY_MOD_SHIFT: u16 = 0x90;
+pub const KEY_MOD_CTRL: u16 = 0x91;
+pub const KEY_MOD_ALT: u16 = 0x92;
+pub const KEY_MOD_SUPER: u16 = 0x93;
+
 /// Mouse button codes (Linux BTN_* values).
 pub const BTN_LEFT: u16 = 0x110;
 pub const BTN_RIGHT: u16 = 0x111;
@@ -49,6 +70,15 @@ impl InputEvent {
             value,
         }
     }
+
+    pub const fn abs(code: u16, value: i32) -> Self {
+        Self {
+            timestamp: 0,
+            event_type: EV_ABS,
+            code,
+            value,
+        }
+    }
 }
 
 /// Ring buffer for input events.
@@ -110,6 +140,9 @@ static EVENT_BUFFER: spin::Mutex<EventBuffer> = spin::Mutex::new(EventBuffer::ne
 ///
 /// Called by keyboard and mouse drivers.
 pub fn push_event(event: InputEvent) {
+    if !super::input_replay::observe(&event) {
+        return;
+    }
     EVENT_BUFFER.lock().push(event);
 }
 
@@ -118,10 +151,133 @@ pub fn read_event() -> Option<InputEvent> {
     EVENT_BUFFER.lock().pop()
 }
 
+// ---------------------------------------------------------------------------
+// Injection
+// ---------------------------------------------------------------------------
+
+/// Capability object for input injection ("INP\0" in the top half).
+const INJECT_OBJECT: ObjectRef = ObjectRef::Device {
+    device_id: 0x494E_5000_0000_0000,
+};
+
+/// Whether process `pid` holds the input-injection capability.
+pub fn may_inject(pid: ProcessId) -> bool {
+    let Some(process) = crate::process::get_process(pid) else {
+        return false;
+    };
+    let mut found = false;
+    let cap_space = process.capability_space.lock();
+    let _ = cap_space.iter_capabilities(|entry| {
+        found = entry.object == INJECT_OBJECT
+            && entry.rights.contains(Rights::WRITE)
+            && cap_manager().is_valid(entry.capability);
+        !found
+    });
+    found
+}
+
+/// Give process `pid` the input-injection capability.
+pub fn grant_inject(pid: ProcessId) -> Result<(), KernelError> {
+    let process =
+        crate::process::get_process(pid).ok_or(KernelError::ProcessNotFound { pid: pid.0 })?;
+    let cap_space = process.capability_space.lock();
+    cap_manager()
+        .create_capability(INJECT_OBJECT, Rights::WRITE, &cap_space)
+        .map_err(|_| KernelError::ResourceExhausted {
+            resource: "capabilities",
+        })?;
+    Ok(())
+}
+
+/// Modifier bit for one of the `KEY_MOD_*` codes.
+fn injected_modifier(code: u16) -> Option<u8> {
+    use super::keyboard::{MOD_ALT, MOD_CTRL, MOD_SHIFT, MOD_SUPER};
+    match code {
+        KEY_MOD_SHIFT => Some(MOD_SHIFT),
+        KEY_MOD_CTRL => Some(MOD_CTRL),
+        KEY_MOD_ALT => Some(MOD_ALT),
+        KEY_MOD_SUPER => Some(MOD_SUPER),
+        _ => None,
+    }
+}
+
+/// Whether [`inject`] accepts `event`.
+fn injectable(event: &InputEvent) -> bool {
+    match event.event_type {
+        EV_KEY => {
+            event.code <= super::keyboard::KEY_DELETE as u16
+                || injected_modifier(event.code).is_some()
+                || (BTN_LEFT..=BTN_MIDDLE).contains(&event.code)
+        }
+        EV_REL => matches!(event.code, REL_X | REL_Y),
+        EV_ABS => matches!(event.code, ABS_X | ABS_Y) && event.value >= 0,
+        _ => false,
+    }
+}
+
+/// Feed synthetic events into the input stream.
+///
+/// Key codes are the bytes the keyboard driver produces (ASCII and the
+/// GUI `KEY_*` codes); only presses are delivered, with held, latched and
+/// locked Shift/Ctrl applied. `KEY_MOD_*` codes press and release
+/// modifiers. Pointer events move the cursor as the mouse would. The
+/// whole batch is rejected if any event is malformed.
+pub fn inject(events: &[InputEvent]) -> Result<(), KernelError> {
+    if !events.iter().all(injectable) {
+        return Err(KernelError::InvalidArgument {
+            name: "input event",
+            value: "unsupported type or code",
+        });
+    }
+    for event in events {
+        let pressed = event.value != 0;
+        match event.event_type {
+            EV_KEY => {
+                if let Some(bit) = injected_modifier(event.code) {
+                    super::keyboard::inject_modifier(bit, pressed);
+                } else if (BTN_LEFT..=BTN_MIDDLE).contains(&event.code) {
+                    push_event(InputEvent::key(event.code, pressed));
+                } else if pressed {
+                    let byte = super::keyboard::inject_key(event.code as u8);
+                    push_event(InputEvent::key(byte as u16, true));
+                }
+            }
+            EV_REL => {
+                let (dx, dy) = if event.code == REL_X {
+                    (event.value, 0)
+                } else {
+                    (0, event.value)
+                };
+                super::mouse::move_cursor(dx, dy);
+                push_event(InputEvent::rel(event.code, event.value));
+            }
+            EV_ABS => {
+                let (x, y) = super::mouse::cursor_position();
+                if event.code == ABS_X {
+                    super::mouse::set_cursor_position(event.value, y);
+                } else {
+                    super::mouse::set_cursor_position(x, event.value);
+                }
+                push_event(InputEvent::abs(event.code, event.value));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 /// Poll all input sources and convert to input events.
 ///
-/// Called periodically (e.g., from APIC timer or shell loop).
+/// Called periodically (e.g., from APIC timer or shell loop). While an
+/// input replay is running, recorded events are delivered instead and live
+/// input is drained and discarded.
 pub fn poll_all() {
+    #[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+    let replaying = super::input_replay::tick();
+
+    // Release keys whose slow-keys delay has passed
+    super::keyboard::tick();
+
     // Poll keyboard and mouse hardware, then drain decoded buffers
     #[cfg(target_arch = "x86_64")]
     {
@@ -162,6 +318,9 @@ pub fn poll_all() {
     #[cfg(target_arch = "x86_64")]
     {
         while let Some(key_byte) = crate::drivers::keyboard::read_key() {
+            if replaying {
+                continue;
+            }
             push_event(InputEvent::key(key_byte as u16, true));
         }
     }
@@ -170,6 +329,9 @@ pub fn poll_all() {
     #[cfg(target_arch = "x86_64")]
     {
         while let Some(mouse_event) = crate::drivers::mouse::read_event() {
+            if replaying {
+                continue;
+            }
             // Relative movement events
             if mouse_event.dx != 0 {
                 push_event(InputEvent::rel(REL_X, mouse_event.dx as i32));
@@ -0,0 +1,727 @@
+//! Deterministic input recording and replay for GUI testing.
+//!
+//! While recording, every event pushed through
+//! [`input_event::push_event`](super::input_event::push_event) is captured
+//! with its offset from the start of the session and the modifier state at
+//! that moment. Pressing Ctrl+Alt+P inserts a *checkpoint*.
+//!
+//! During replay, [`tick`] is called at the top of
+//! [`input_event::poll_all`](super::input_event::poll_all). It feeds the
+//! recorded events back into the input subsystem at their original pacing,
+//! restoring the cursor and modifier state they were recorded with. Live
+//! hardware input is discarded for the duration. At each checkpoint the
+//! current frame is captured with [`crate::desktop::screenshot`], saved as
+//! `/tmp/replay/checkpoint-N.bmp`, and compared against
+//! `<refs>/checkpoint-N.bmp` if a reference directory was given. Results are
+//! reported on the serial console as `[REPLAY] ...` lines so a QEMU harness
+//! can grep for `[REPLAY] PASS` / `[REPLAY] FAIL`.
+//!
+//! Sessions can be driven from the shell (`inputrec`) or from the kernel
+//! command line when the desktop starts:
+//!
+//! ```text
+//! input.record=/tmp/session.rec
+//! input.replay=/usr/share/tests/login.rec input.replay_refs=/usr/share/tests/login
+//! ```
+//!
+//! `inputrec queue <file> [refdir]` does the same as `input.replay` without
+//! rebuilding the kernel: the replay starts the next time the desktop does,
+//! so its pacing is measured from the first frame rather than from the
+//! moment the shell command ran.
+//!
+//! # File format
+//!
+//! Little-endian. A 20-byte header (`"VIRP"`, version `u16`, reserved `u16`,
+//! starting cursor x/y `i32`, record count `u32`) followed by 16-byte
+//! records: offset in ms `u32`, type `u16`, code `u16`, value `i32`,
+//! modifiers `u8`, 3 bytes of padding.
+
+#[cfg(feature = "desktop")]
+use alloc::format;
+use alloc::{string::String, vec::Vec};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use spin::Mutex;
+
+use super::input_event::{self, InputEvent, EV_KEY, EV_REL, REL_X, REL_Y};
+use crate::error::KernelError;
+
+/// File magic.
+const MAGIC: &[u8; 4] = b"VIRP";
+
+/// Current file format version.
+const VERSION: u16 = 1;
+
+/// Size of the file header in bytes.
+const HEADER_SIZE: usize = 20;
+
+/// Size of one encoded record in bytes.
+const RECORD_SIZE: usize = 16;
+
+/// Pseudo event type marking a screenshot checkpoint; `code` holds the
+/// checkpoint index. Never delivered to the input subsystem.
+pub const EV_CHECKPOINT: u16 = 0x7F00;
+
+/// Upper bound on events captured in a single recording.
+const MAX_RECORDS: usize = 65536;
+
+/// Directory replay checkpoint screenshots are written to.
+#[cfg(feature = "desktop")]
+const OUTPUT_DIR: &str = "/tmp/replay";
+
+/// Session modes (stored in [`MODE`]).
+const MODE_IDLE: u8 = 0;
+const MODE_RECORDING: u8 = 1;
+const MODE_REPLAYING: u8 = 2;
+
+/// A single recorded event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    /// Milliseconds since the start of the recording.
+    pub offset_ms: u32,
+    pub event_type: u16,
+    pub code: u16,
+    pub value: i32,
+    /// Keyboard modifier bitmask when the event was recorded.
+    pub modifiers: u8,
+}
+
+impl Record {
+    fn is_checkpoint(&self) -> bool {
+        self.event_type == EV_CHECKPOINT
+    }
+}
+
+/// A complete recording: starting cursor position and the event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub start_cursor: (i32, i32),
+    pub records: Vec<Record>,
+}
+
+impl Recording {
+    /// Number of checkpoints in the recording.
+    pub fn checkpoints(&self) -> usize {
+        self.records.iter().filter(|r| r.is_checkpoint()).count()
+    }
+
+    /// Serialize to the on-disk format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.records.len() * RECORD_SIZE);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&self.start_cursor.0.to_le_bytes());
+        out.extend_from_slice(&self.start_cursor.1.to_le_bytes());
+        out.extend_from_slice(&(self.records.len() as u32).to_le_bytes());
+        for r in &self.records {
+            out.extend_from_slice(&r.offset_ms.to_le_bytes());
+            out.extend_from_slice(&r.event_type.to_le_bytes());
+            out.extend_from_slice(&r.code.to_le_bytes());
+            out.extend_from_slice(&r.value.to_le_bytes());
+            out.push(r.modifiers);
+            out.extend_from_slice(&[0u8; 3]);
+        }
+        out
+    }
+
+    /// Parse the on-disk format.
+    pub fn decode(data: &[u8]) -> Result<Self, KernelError> {
+        let invalid = |value| KernelError::InvalidArgument {
+            name: "input recording",
+            value,
+        };
+        if data.len() < HEADER_SIZE || &data[0..4] != MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        let u16_at = |off: usize| u16::from_le_bytes([data[off], data[off + 1]]);
+        let u32_at = |off: usize| {
+            u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+        };
+        if u16_at(4) != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let start_cursor = (u32_at(8) as i32, u32_at(12) as i32);
+        let count = u32_at(16) as usize;
+        if count > MAX_RECORDS || data.len() < HEADER_SIZE + count * RECORD_SIZE {
+            return Err(invalid("truncated"));
+        }
+
+        let mut records = Vec::with_capacity(count);
+        let mut last = 0;
+        for i in 0..count {
+            let off = HEADER_SIZE + i * RECORD_SIZE;
+            let record = Record {
+                offset_ms: u32_at(off),
+                event_type: u16_at(off + 4),
+                code: u16_at(off + 6),
+                value: u32_at(off + 8) as i32,
+                modifiers: data[off + 12],
+            };
+            if record.offset_ms < last {
+                return Err(invalid("records out of order"));
+            }
+            last = record.offset_ms;
+            records.push(record);
+        }
+        Ok(Self {
+            start_cursor,
+            records,
+        })
+    }
+}
+
+/// Snapshot of the current session, for `inputrec status`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionStatus {
+    pub recording: bool,
+    pub replaying: bool,
+    /// Events recorded so far, or replayed so far.
+    pub events: usize,
+    /// Total events in the recording being replayed.
+    pub total: usize,
+    pub checkpoints: u32,
+    pub mismatches: u32,
+}
+
+/// Outcome of a finished replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayResult {
+    pub checkpoints: u32,
+    pub mismatches: u32,
+    /// `false` if the replay was stopped before reaching the end.
+    pub completed: bool,
+}
+
+impl ReplayResult {
+    pub fn passed(&self) -> bool {
+        self.completed && self.mismatches == 0
+    }
+}
+
+enum Session {
+    Recording {
+        path: String,
+        start_ms: u64,
+        recording: Recording,
+        next_checkpoint: u16,
+    },
+    Replaying {
+        start_ms: u64,
+        recording: Recording,
+        next: usize,
+        refs: Option<String>,
+        checkpoints: u32,
+        mismatches: u32,
+    },
+}
+
+/// Fast-path copy of the session mode, checked on every input poll.
+static MODE: AtomicU8 = AtomicU8::new(MODE_IDLE);
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+/// Replay (path, reference directory) to start when the desktop starts.
+static QUEUED: Mutex<Option<(String, Option<String>)>> = Mutex::new(None);
+
+fn now_ms() -> u64 {
+    crate::arch::timer::get_timestamp_ms()
+}
+
+/// Start recording input events; they are written to `path` on [`stop`].
+pub fn start_recording(path: &str) -> Result<(), KernelError> {
+    let mut session = SESSION.lock();
+    if session.is_some() {
+        return Err(KernelError::InvalidState {
+            expected: "no input session",
+            actual: "session active",
+        });
+    }
+    *session = Some(Session::Recording {
+        path: String::from(path),
+        start_ms: now_ms(),
+        recording: Recording {
+            start_cursor: super::mouse::cursor_position(),
+            records: Vec::new(),
+        },
+        next_checkpoint: 0,
+    });
+    MODE.store(MODE_RECORDING, Ordering::Release);
+    Ok(())
+}
+
+/// Insert a checkpoint into the active recording. Returns its index.
+pub fn mark() -> Result<u16, KernelError> {
+    match SESSION.lock().as_mut() {
+        Some(Session::Recording {
+            start_ms,
+            recording,
+            next_checkpoint,
+            ..
+        }) => {
+            let index = *next_checkpoint;
+            *next_checkpoint += 1;
+            recording.records.push(Record {
+                offset_ms: now_ms().saturating_sub(*start_ms) as u32,
+                event_type: EV_CHECKPOINT,
+                code: index,
+                value: 0,
+                modifiers: 0,
+            });
+            Ok(index)
+        }
+        _ => Err(KernelError::InvalidState {
+            expected: "recording",
+            actual: "not recording",
+        }),
+    }
+}
+
+/// Load `path` and start replaying it. Checkpoints are compared against
+/// `refs/checkpoint-N.bmp` when `refs` is given. Returns the event count.
+pub fn start_replay(path: &str, refs: Option<&str>) -> Result<usize, KernelError> {
+    let recording = Recording::decode(&crate::fs::read_file(path)?)?;
+    let count = recording.records.len();
+
+    let mut session = SESSION.lock();
+    if session.is_some() {
+        return Err(KernelError::InvalidState {
+            expected: "no input session",
+            actual: "session active",
+        });
+    }
+    let (x, y) = recording.start_cursor;
+    super::mouse::set_cursor_position(x, y);
+    super::keyboard::set_modifiers(0);
+    *session = Some(Session::Replaying {
+        start_ms: now_ms(),
+        recording,
+        next: 0,
+        refs: refs.map(String::from),
+        checkpoints: 0,
+        mismatches: 0,
+    });
+    MODE.store(MODE_REPLAYING, Ordering::Release);
+    crate::println!("[REPLAY] Replaying {} events from {}", count, path);
+    Ok(count)
+}
+
+/// Arrange for `path` to be replayed when the desktop next enters its
+/// render loop, replacing any replay queued before. The file is checked
+/// now so that mistakes are reported at the shell.
+pub fn queue_replay(path: &str, refs: Option<&str>) -> Result<usize, KernelError> {
+    let recording = Recording::decode(&crate::fs::read_file(path)?)?;
+    *QUEUED.lock() = Some((String::from(path), refs.map(String::from)));
+    crate::println!(
+        "[REPLAY] Queued {} events from {} for desktop start",
+        recording.records.len(),
+        path
+    );
+    Ok(recording.records.len())
+}
+
+/// Whether a replay is in progress (live input is being discarded).
+pub fn is_replaying() -> bool {
+    MODE.load(Ordering::Acquire) == MODE_REPLAYING
+}
+
+/// Current session status.
+pub fn status() -> SessionStatus {
+    match SESSION.lock().as_ref() {
+        None => SessionStatus::default(),
+        Some(Session::Recording {
+            recording,
+            next_checkpoint,
+            ..
+        }) => SessionStatus {
+            recording: true,
+            events: recording.records.len(),
+            checkpoints: *next_checkpoint as u32,
+            ..SessionStatus::default()
+        },
+        Some(Session::Replaying {
+            recording,
+            next,
+            checkpoints,
+            mismatches,
+            ..
+        }) => SessionStatus {
+            replaying: true,
+            events: *next,
+            total: recording.records.len(),
+            checkpoints: *checkpoints,
+            mismatches: *mismatches,
+            ..SessionStatus::default()
+        },
+    }
+}
+
+/// End the active session.
+///
+/// A recording is written to its file and `Ok(None)` is returned. A replay
+/// in progress is aborted and its (incomplete) result returned.
+pub fn stop() -> Result<Option<ReplayResult>, KernelError> {
+    let session = SESSION.lock().take();
+    MODE.store(MODE_IDLE, Ordering::Release);
+    match session {
+        None => Err(KernelError::InvalidState {
+            expected: "input session active",
+            actual: "idle",
+        }),
+        Some(Session::Recording {
+            path, recording, ..
+        }) => {
+            crate::fs::write_file(&path, &recording.encode())?;
+            crate::println!(
+                "[REPLAY] Recorded {} events ({} checkpoints) to {}",
+                recording.records.len(),
+                recording.checkpoints(),
+                path
+            );
+            Ok(None)
+        }
+        Some(Session::Replaying {
+            checkpoints,
+            mismatches,
+            ..
+        }) => {
+            let result = ReplayResult {
+                checkpoints,
+                mismatches,
+                completed: false,
+            };
+            report(&result);
+            Ok(Some(result))
+        }
+    }
+}
+
+/// Observe an event on its way into the event queue.
+///
+/// Returns `false` if the event must not be delivered (the checkpoint
+/// hotkey is consumed while recording).
+pub(super) fn observe(event: &InputEvent) -> bool {
+    if MODE.load(Ordering::Acquire) != MODE_RECORDING {
+        return true;
+    }
+    let modifiers = super::keyboard::get_modifiers();
+
+    use super::keyboard::{MOD_ALT, MOD_CTRL};
+    let hotkey = event.event_type == EV_KEY
+        && event.value == 1
+        && modifiers & (MOD_CTRL | MOD_ALT) == (MOD_CTRL | MOD_ALT)
+        && matches!(event.code, 0x10 | 0x50 | 0x70); // Ctrl-P, 'P', 'p'
+    if hotkey {
+        if let Ok(index) = mark() {
+            crate::serial::_serial_print(format_args!("[REPLAY] Checkpoint {} marked\n", index));
+        }
+        return false;
+    }
+
+    if let Some(Session::Recording {
+        start_ms,
+        recording,
+        ..
+    }) = SESSION.lock().as_mut()
+    {
+        if recording.records.len() < MAX_RECORDS {
+            recording.records.push(Record {
+                offset_ms: now_ms().saturating_sub(*start_ms) as u32,
+                event_type: event.event_type,
+                code: event.code,
+                value: event.value,
+                modifiers,
+            });
+        }
+    }
+    true
+}
+
+/// What [`tick`] decided to do once the session lock is released.
+enum Step {
+    Deliver(Vec<Record>),
+    Checkpoint(u16, Option<String>),
+    Finished(ReplayResult),
+}
+
+/// Advance the replay, delivering every event that is due.
+///
+/// Returns `true` while a replay is active, in which case the caller must
+/// discard live hardware input.
+pub fn tick() -> bool {
+    if MODE.load(Ordering::Acquire) != MODE_REPLAYING {
+        return false;
+    }
+
+    let step = {
+        let mut guard = SESSION.lock();
+        let Some(Session::Replaying {
+            start_ms,
+            recording,
+            next,
+            refs,
+            checkpoints,
+            mismatches,
+        }) = guard.as_mut()
+        else {
+            return false;
+        };
+        let elapsed = now_ms().saturating_sub(*start_ms);
+        let pending = &recording.records[*next..];
+
+        match pending.first() {
+            None => {
+                let result = ReplayResult {
+                    checkpoints: *checkpoints,
+                    mismatches: *mismatches,
+                    completed: true,
+                };
+                *guard = None;
+                MODE.store(MODE_IDLE, Ordering::Release);
+                Step::Finished(result)
+            }
+            Some(r) if (r.offset_ms as u64) > elapsed => return true,
+            Some(r) if r.is_checkpoint() => {
+                *next += 1;
+                Step::Checkpoint(r.code, refs.clone())
+            }
+            Some(first) => {
+                // Deliver due events up to the next checkpoint. Stop at a
+                // modifier change too: the desktop samples the modifier
+                // state once per poll, so each batch must share one.
+                let mods = first.modifiers;
+                let batch: Vec<Record> = pending
+                    .iter()
+                    .take_while(|r| {
+                        (r.offset_ms as u64) <= elapsed && !r.is_checkpoint() && r.modifiers == mods
+                    })
+                    .copied()
+                    .collect();
+                *next += batch.len();
+                Step::Deliver(batch)
+            }
+        }
+    };
+
+    match step {
+        Step::Deliver(batch) => {
+            for r in batch {
+                deliver(&r);
+            }
+        }
+        Step::Checkpoint(index, refs) => {
+            let matched = checkpoint(index, refs.as_deref());
+            if let Some(Session::Replaying {
+                checkpoints,
+                mismatches,
+                ..
+            }) = SESSION.lock().as_mut()
+            {
+                *checkpoints += 1;
+                if !matched {
+                    *mismatches += 1;
+                }
+            }
+        }
+        Step::Finished(result) => report(&result),
+    }
+    true
+}
+
+/// Replay one recorded event into the input subsystem.
+fn deliver(r: &Record) {
+    super::keyboard::set_modifiers(r.modifiers);
+    if r.event_type == EV_REL {
+        match r.code {
+            REL_X => super::mouse::move_cursor(r.value, 0),
+            REL_Y => super::mouse::move_cursor(0, r.value),
+            _ => {}
+        }
+    }
+    input_event::push_event(InputEvent {
+        timestamp: 0,
+        event_type: r.event_type,
+        code: r.code,
+        value: r.value,
+    });
+}
+
+/// Capture checkpoint `index`, save it, and compare it against the
+/// reference image if one was given. Returns `false` on mismatch or error.
+#[cfg(feature = "desktop")]
+fn checkpoint(index: u16, refs: Option<&str>) -> bool {
+    let shot = match crate::desktop::screenshot::capture() {
+        Ok(shot) => shot,
+        Err(e) => {
+            crate::println!("[REPLAY] checkpoint {}: capture failed: {:?}", index, e);
+            return false;
+        }
+    };
+
+    let name = format!("checkpoint-{}.bmp", index);
+    let _ = crate::fs::get_vfs()
+        .read()
+        .mkdir(OUTPUT_DIR, crate::fs::Permissions::default());
+    if let Err(e) = shot.save(&format!("{}/{}", OUTPUT_DIR, name)) {
+        crate::println!("[REPLAY] checkpoint {}: save failed: {:?}", index, e);
+    }
+
+    let Some(refs) = refs else {
+        crate::println!("[REPLAY] checkpoint {}: captured", index);
+        return true;
+    };
+    let reference =
+        match crate::desktop::screenshot::Screenshot::load(&format!("{}/{}", refs, name)) {
+            Ok(reference) => reference,
+            Err(e) => {
+                crate::println!("[REPLAY] checkpoint {}: no reference: {:?}", index, e);
+                return false;
+            }
+        };
+    match shot.diff(&reference) {
+        Some(0) => {
+            crate::println!("[REPLAY] checkpoint {}: match", index);
+            true
+        }
+        Some(n) => {
+            crate::println!(
+                "[REPLAY] checkpoint {}: mismatch ({} pixels differ)",
+                index,
+                n
+            );
+            false
+        }
+        None => {
+            crate::println!(
+                "[REPLAY] checkpoint {}: mismatch (size {}x{} vs {}x{})",
+                index,
+                shot.width,
+                shot.height,
+                reference.width,
+                reference.height
+            );
+            false
+        }
+    }
+}
+
+/// Without the desktop there is nothing to capture, so every checkpoint
+/// fails.
+#[cfg(not(feature = "desktop"))]
+fn checkpoint(index: u16, _refs: Option<&str>) -> bool {
+    crate::println!(
+        "[REPLAY] checkpoint {}: no screenshot support in this build",
+        index
+    );
+    false
+}
+
+fn report(result: &ReplayResult) {
+    crate::println!(
+        "[REPLAY] {} ({} checkpoints, {} mismatches{})",
+        if result.passed() { "PASS" } else { "FAIL" },
+        result.checkpoints,
+        result.mismatches,
+        if result.completed { "" } else { ", aborted" }
+    );
+}
+
+/// Start a session queued with [`queue_replay`] or requested on the kernel
+/// command line, if any. A queued replay takes precedence.
+///
+/// Called by the desktop when it enters the render loop.
+pub fn start_requested() {
+    use crate::utils::cmdline::param;
+
+    let queued = QUEUED.lock().take();
+    let replay = queued.or_else(|| {
+        param("input.replay")
+            .filter(|p| !p.is_empty())
+            .map(|path| (path, param("input.replay_refs").filter(|p| !p.is_empty())))
+    });
+
+    if let Some((path, refs)) = replay {
+        if let Err(e) = start_replay(&path, refs.as_deref()) {
+            crate::println!("[REPLAY] Cannot replay {}: {:?}", path, e);
+            crate::println!("[REPLAY] FAIL (replay did not start)");
+        }
+    } else if let Some(path) = param("input.record").filter(|p| !p.is_empty()) {
+        match start_recording(&path) {
+            Ok(()) => crate::println!("[REPLAY] Recording input to {}", path),
+            Err(e) => crate::println!("[REPLAY] Cannot record to {}: {:?}", path, e),
+        }
+    }
+}
+
+/// End any session still active when the desktop exits.
+pub fn finish_session() {
+    if MODE.load(Ordering::Acquire) != MODE_IDLE {
+        if let Err(e) = stop() {
+            crate::println!("[REPLAY] Failed to finish input session: {:?}", e);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn sample() -> Recording {
+        Recording {
+            start_cursor: (100, -3),
+            records: vec![
+                Record {
+                    offset_ms: 0,
+                    event_type: EV_REL,
+                    code: REL_X,
+                    value: -12,
+                    modifiers: 0,
+                },
+                Record {
+                    offset_ms: 40,
+                    event_type: EV_KEY,
+                    code: b'a' as u16,
+                    value: 1,
+                    modifiers: super::super::keyboard::MOD_SHIFT,
+                },
+                Record {
+                    offset_ms: 500,
+                    event_type: EV_CHECKPOINT,
+                    code: 0,
+                    value: 0,
+                    modifiers: 0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let rec = sample();
+        let data = rec.encode();
+        assert_eq!(data.len(), HEADER_SIZE + 3 * RECORD_SIZE);
+        assert_eq!(Recording::decode(&data).unwrap(), rec);
+        assert_eq!(rec.checkpoints(), 1);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_input() {
+        let data = sample().encode();
+        assert!(Recording::decode(&data[..data.len() - 1]).is_err());
+        let mut bad = data.clone();
+        bad[0] = b'X';
+        assert!(Recording::decode(&bad).is_err());
+        let mut bad = data.clone();
+        bad[4] = 9;
+        assert!(Recording::decode(&bad).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_unordered() {
+        let mut rec = sample();
+        rec.records.swap(0, 1);
+        assert!(Recording::decode(&rec.encode()).is_err());
+    }
+}
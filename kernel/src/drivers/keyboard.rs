@@ -2,12 +2,23 @@
 //!
 //! Reads scancodes from I/O port 0x60, decodes them via the `pc_keyboard`
 //! crate (ScancodeSet1, US 104-key layout), and pushes decoded ASCII bytes
-//! to a lock-free ring buffer. The shell reads from this buffer.
+//! to a ring buffer. The shell reads from this buffer. Key events
+//! pass through the sticky/slow keys filter in [`super::access_keys`] on
+//! the way.
 //!
-//! On non-x86_64 architectures, all functions are no-op stubs.
+//! The decoder, the buffer and the filter are shared with the keyboard
+//! interrupt handler, so each lives in a [`DriverCell`].
+//!
+//! On non-x86_64 architectures the hardware functions are no-op stubs;
+//! injected input still goes through the filter.
 
 use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
+use super::{
+    access_keys::{self, AccessKeys},
+    driver_common::DriverCell,
+};
+
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 /// Check if the keyboard driver has been initialized.
@@ -30,9 +41,72 @@ pub const MOD_SUPER: u8 = 0x08;
 
 static MODIFIER_STATE: AtomicU8 = AtomicU8::new(0);
 
-/// Get the current modifier key bitmask.
+/// Modifiers latched or locked by sticky keys, mirrored from [`ACCESS`] so
+/// [`get_modifiers`] does not need the lock.
+static STICKY_STATE: AtomicU8 = AtomicU8::new(0);
+
+/// Get the current modifier key bitmask, including modifiers latched or
+/// locked by sticky keys.
 pub fn get_modifiers() -> u8 {
-    MODIFIER_STATE.load(Ordering::Relaxed)
+    MODIFIER_STATE.load(Ordering::Relaxed) | STICKY_STATE.load(Ordering::Relaxed)
+}
+
+/// Update a modifier bit in the global modifier state.
+fn update_modifier(bit: u8, down: bool) {
+    if down {
+        MODIFIER_STATE.fetch_or(bit, Ordering::Relaxed);
+    } else {
+        MODIFIER_STATE.fetch_and(!bit, Ordering::Relaxed);
+    }
+}
+
+/// Overwrite the modifier key bitmask.
+///
+/// Used by input replay so that replayed key events see the modifiers
+/// that were held when they were recorded.
+pub fn set_modifiers(mods: u8) {
+    MODIFIER_STATE.store(mods, Ordering::Relaxed);
+}
+
+// ---------------------------------------------------------------------------
+// Accessibility filtering
+// ---------------------------------------------------------------------------
+
+/// The key a slow-keys press is deferred as.
+#[cfg(target_arch = "x86_64")]
+type PendingKey = pc_keyboard::KeyCode;
+/// Nothing is deferred without the PS/2 decoder; only injected keys and
+/// modifiers go through the filter.
+#[cfg(not(target_arch = "x86_64"))]
+type PendingKey = u8;
+
+static ACCESS: DriverCell<AccessKeys<PendingKey>> = DriverCell::new(AccessKeys::new());
+
+/// Run `f` on the sticky/slow keys filter and republish its modifiers.
+fn with_access<R>(f: impl FnOnce(&mut AccessKeys<PendingKey>) -> R) -> R {
+    let mut access = ACCESS.lock();
+    let result = f(&mut access);
+    STICKY_STATE.store(access.sticky_modifiers(), Ordering::Relaxed);
+    result
+}
+
+/// Turn sticky keys on or off and set the slow-keys delay (0 disables it).
+pub fn set_access_options(sticky_keys: bool, slow_keys_ms: u32) {
+    with_access(|access| access.configure(sticky_keys, slow_keys_ms));
+}
+
+/// Press or release a modifier on behalf of an input injector. It counts
+/// as held, for hotkeys and sticky keys alike, until released.
+pub fn inject_modifier(bit: u8, down: bool) {
+    update_modifier(bit, down);
+    with_access(|access| access.modifier(bit, down));
+}
+
+/// Filter a key byte from an input injector: held, latched and locked
+/// Shift and Ctrl are applied and latches are used up, as for a typed key.
+pub fn inject_key(byte: u8) -> u8 {
+    let mods = with_access(AccessKeys::injected_key);
+    access_keys::apply_modifiers(byte, mods)
 }
 
 // ---------------------------------------------------------------------------
@@ -70,67 +144,56 @@ pub const KEY_DELETE: u8 = 0x86;
 
 #[cfg(target_arch = "x86_64")]
 mod x86_64_impl {
-    use core::sync::atomic::AtomicUsize;
-
-    use pc_keyboard::{layouts, DecodedKey, HandleControl, Keyboard, ScancodeSet1};
-    use spin::Mutex;
+    use pc_keyboard::{
+        layouts, DecodedKey, HandleControl, KeyCode, KeyEvent, KeyState, Keyboard, ScancodeSet1,
+    };
 
     use super::*;
 
     /// Ring buffer size for decoded key bytes (must be power of 2).
     const KEY_BUFFER_SIZE: usize = 256;
 
-    /// Lock-free single-producer single-consumer ring buffer for decoded keys.
+    /// Ring buffer for decoded keys, filled by the interrupt handler and
+    /// drained by the shell.
     struct KeyBuffer {
         buf: [u8; KEY_BUFFER_SIZE],
-        head: AtomicUsize,
-        tail: AtomicUsize,
+        head: usize,
+        tail: usize,
     }
 
     impl KeyBuffer {
         const fn new() -> Self {
             Self {
                 buf: [0; KEY_BUFFER_SIZE],
-                head: AtomicUsize::new(0),
-                tail: AtomicUsize::new(0),
+                head: 0,
+                tail: 0,
             }
         }
 
-        /// Push a byte (called from interrupt handler -- single producer).
+        /// Push a byte, dropping it if the buffer is full.
         fn push(&mut self, byte: u8) {
-            let head = self.head.load(Ordering::Relaxed);
-            let next = (head + 1) & (KEY_BUFFER_SIZE - 1);
-            let tail = self.tail.load(Ordering::Acquire);
-            if next == tail {
-                return; // Buffer full, drop key
+            let next = (self.head + 1) & (KEY_BUFFER_SIZE - 1);
+            if next == self.tail {
+                return;
             }
-            self.buf[head] = byte;
-            self.head.store(next, Ordering::Release);
+            self.buf[self.head] = byte;
+            self.head = next;
         }
 
-        /// Pop a byte (called from shell main loop -- single consumer).
-        fn pop(&self) -> Option<u8> {
-            let tail = self.tail.load(Ordering::Relaxed);
-            let head = self.head.load(Ordering::Acquire);
-            if tail == head {
+        /// Pop the oldest byte.
+        fn pop(&mut self) -> Option<u8> {
+            if self.tail == self.head {
                 return None;
             }
-            let byte = self.buf[tail];
-            self.tail
-                .store((tail + 1) & (KEY_BUFFER_SIZE - 1), Ordering::Release);
+            let byte = self.buf[self.tail];
+            self.tail = (self.tail + 1) & (KEY_BUFFER_SIZE - 1);
             Some(byte)
         }
     }
 
-    // SAFETY: KeyBuffer uses atomic operations for head/tail synchronization.
-    // The push side (interrupt handler) is single-producer and pop side
-    // (shell loop) is single-consumer, making concurrent access safe.
-    unsafe impl Send for KeyBuffer {}
-    unsafe impl Sync for KeyBuffer {}
-
-    static KEY_BUFFER: Mutex<KeyBuffer> = Mutex::new(KeyBuffer::new());
+    static KEY_BUFFER: DriverCell<KeyBuffer> = DriverCell::new(KeyBuffer::new());
 
-    static KEYBOARD: Mutex<Option<Keyboard<layouts::Us104Key, ScancodeSet1>>> = Mutex::new(None);
+    static KEYBOARD: DriverCell<Option<Decoder>> = DriverCell::new(None);
 
     /// Initialize the PS/2 keyboard driver.
     pub fn init() {
@@ -139,17 +202,17 @@ mod x86_64_impl {
             layouts::Us104Key,
             HandleControl::MapLettersToUnicode,
         );
-        *KEYBOARD.lock() = Some(kb);
+        KEYBOARD.set(kb);
         INITIALIZED.store(true, Ordering::Release);
     }
 
+    type Decoder = Keyboard<layouts::Us104Key, ScancodeSet1>;
+
     /// Handle a scancode from the PS/2 keyboard interrupt (vector 33).
     ///
     /// This function must NOT call println! or acquire any spinlock used
     /// by the serial/fbcon output path.
     pub fn handle_scancode(scancode: u8) {
-        use pc_keyboard::KeyCode;
-
         let mut kb_guard = KEYBOARD.lock();
         if let Some(ref mut keyboard) = *kb_guard {
             if let Ok(Some(key_event)) = keyboard.add_byte(scancode) {
@@ -157,69 +220,103 @@ mod x86_64_impl {
                 // process_keyevent consumes it. Modifiers are tracked
                 // globally so the render loop can detect hotkeys.
                 let code = key_event.code;
-                let is_down = key_event.state == pc_keyboard::KeyState::Down;
-                match code {
-                    KeyCode::LShift | KeyCode::RShift => update_modifier(MOD_SHIFT, is_down),
-                    KeyCode::LControl | KeyCode::RControl => update_modifier(MOD_CTRL, is_down),
-                    KeyCode::LAlt | KeyCode::RAltGr => update_modifier(MOD_ALT, is_down),
-                    KeyCode::LWin | KeyCode::RWin => update_modifier(MOD_SUPER, is_down),
-                    _ => {}
+                let is_down = key_event.state == KeyState::Down;
+                let modifier = match code {
+                    KeyCode::LShift | KeyCode::RShift => Some(MOD_SHIFT),
+                    KeyCode::LControl | KeyCode::RControl => Some(MOD_CTRL),
+                    KeyCode::LAlt | KeyCode::RAltGr => Some(MOD_ALT),
+                    KeyCode::LWin | KeyCode::RWin => Some(MOD_SUPER),
+                    _ => None,
+                };
+                if let Some(bit) = modifier {
+                    update_modifier(bit, is_down);
+                    with_access(|access| access.modifier(bit, is_down));
+                    let _ = keyboard.process_keyevent(key_event);
+                    return;
                 }
 
-                if let Some(key) = keyboard.process_keyevent(key_event) {
-                    match key {
-                        DecodedKey::Unicode(ch) => {
-                            if ch.is_ascii() {
-                                KEY_BUFFER.lock().push(ch as u8);
-                            }
-                        }
-                        DecodedKey::RawKey(key) => {
-                            if GUI_MODE.load(Ordering::Relaxed) {
-                                // GUI mode: emit single-byte codes for special
-                                // keys to avoid ANSI escape sequence conflicts.
-                                let gui_byte = match key {
-                                    KeyCode::ArrowUp => Some(KEY_UP),
-                                    KeyCode::ArrowDown => Some(KEY_DOWN),
-                                    KeyCode::ArrowRight => Some(KEY_RIGHT),
-                                    KeyCode::ArrowLeft => Some(KEY_LEFT),
-                                    KeyCode::Home => Some(KEY_HOME),
-                                    KeyCode::End => Some(KEY_END),
-                                    KeyCode::Delete => Some(KEY_DELETE),
-                                    _ => None,
-                                };
-                                if let Some(byte) = gui_byte {
-                                    KEY_BUFFER.lock().push(byte);
-                                }
-                            } else {
-                                // Shell mode: emit ANSI escape sequences as before.
-                                let seq: &[u8] = match key {
-                                    KeyCode::ArrowUp => b"\x1b[A",
-                                    KeyCode::ArrowDown => b"\x1b[B",
-                                    KeyCode::ArrowRight => b"\x1b[C",
-                                    KeyCode::ArrowLeft => b"\x1b[D",
-                                    KeyCode::Home => b"\x1b[H",
-                                    KeyCode::End => b"\x1b[F",
-                                    KeyCode::Delete => b"\x1b[3~",
-                                    _ => b"",
-                                };
-                                let mut buf = KEY_BUFFER.lock();
-                                for &byte in seq {
-                                    buf.push(byte);
-                                }
-                            }
-                        }
+                // Other keys may be held back by slow keys
+                let now = crate::arch::timer::get_timestamp_ms();
+                let accepted = with_access(|access| {
+                    if is_down {
+                        access.key_down(code, now)
+                    } else {
+                        access.key_up(code, now)
                     }
+                });
+                if let Some(code) = accepted {
+                    decode_key(keyboard, KeyEvent::new(code, KeyState::Down));
+                }
+                if !is_down {
+                    let _ = keyboard.process_keyevent(key_event);
                 }
             }
         }
     }
 
-    /// Update a modifier bit in the global modifier state.
-    fn update_modifier(bit: u8, down: bool) {
-        if down {
-            MODIFIER_STATE.fetch_or(bit, Ordering::Relaxed);
-        } else {
-            MODIFIER_STATE.fetch_and(!bit, Ordering::Relaxed);
+    /// Deliver a key whose slow-keys delay has run out. Called from the
+    /// input poll loop.
+    pub fn tick() {
+        let now = crate::arch::timer::get_timestamp_ms();
+        let Some(code) = with_access(|access| access.poll(now)) else {
+            return;
+        };
+        if let Some(ref mut keyboard) = *KEYBOARD.lock() {
+            decode_key(keyboard, KeyEvent::new(code, KeyState::Down));
+        }
+    }
+
+    /// Decode a key press and push the resulting bytes.
+    fn decode_key(keyboard: &mut Decoder, key_event: KeyEvent) {
+        let Some(key) = keyboard.process_keyevent(key_event) else {
+            return;
+        };
+        match key {
+            DecodedKey::Unicode(ch) => {
+                if ch.is_ascii() {
+                    // Latched and locked modifiers are invisible to the
+                    // decoder, so apply them to its output
+                    let sticky = STICKY_STATE.load(Ordering::Relaxed);
+                    KEY_BUFFER
+                        .lock()
+                        .push(access_keys::apply_modifiers(ch as u8, sticky));
+                }
+            }
+            DecodedKey::RawKey(key) => {
+                if GUI_MODE.load(Ordering::Relaxed) {
+                    // GUI mode: emit single-byte codes for special
+                    // keys to avoid ANSI escape sequence conflicts.
+                    let gui_byte = match key {
+                        KeyCode::ArrowUp => Some(KEY_UP),
+                        KeyCode::ArrowDown => Some(KEY_DOWN),
+                        KeyCode::ArrowRight => Some(KEY_RIGHT),
+                        KeyCode::ArrowLeft => Some(KEY_LEFT),
+                        KeyCode::Home => Some(KEY_HOME),
+                        KeyCode::End => Some(KEY_END),
+                        KeyCode::Delete => Some(KEY_DELETE),
+                        _ => None,
+                    };
+                    if let Some(byte) = gui_byte {
+                        KEY_BUFFER.lock().push(byte);
+                    }
+                } else {
+                    // Shell mode: emit ANSI escape sequences as before.
+                    let seq: &[u8] = match key {
+                        KeyCode::ArrowUp => b"\x1b[A",
+                        KeyCode::ArrowDown => b"\x1b[B",
+                        KeyCode::ArrowRight => b"\x1b[C",
+                        KeyCode::ArrowLeft => b"\x1b[D",
+                        KeyCode::Home => b"\x1b[H",
+                        KeyCode::End => b"\x1b[F",
+                        KeyCode::Delete => b"\x1b[3~",
+                        _ => b"",
+                    };
+                    let mut buf = KEY_BUFFER.lock();
+                    for &byte in seq {
+                        buf.push(byte);
+                    }
+                }
+            }
         }
     }
 
@@ -230,7 +327,7 @@ mod x86_64_impl {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub use x86_64_impl::{handle_scancode, init, read_key};
+pub use x86_64_impl::{handle_scancode, init, read_key, tick};
 
 // ---------------------------------------------------------------------------
 // Stubs for non-x86_64 architectures
@@ -243,3 +340,6 @@ pub fn init() {}
 pub fn read_key() -> Option<u8> {
     None
 }
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn tick() {}
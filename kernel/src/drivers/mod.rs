@@ -3,9 +3,14 @@
 //! Contains all device drivers including bus drivers, network drivers, and
 //! device-specific drivers.
 
+pub mod access_keys;
 pub mod ahci;
+pub mod block;
 pub mod bluetooth;
 pub mod console;
+pub mod driver_common;
+pub mod dt;
+#[cfg(feature = "net")]
 pub mod e1000;
 pub mod evdev;
 pub mod gpu;
@@ -14,28 +19,35 @@ pub mod gpu_i915;
 pub mod gpu_nouveau;
 pub mod input;
 pub mod input_event;
+pub mod input_replay;
 pub mod iommu;
 pub mod keyboard;
 pub mod mouse;
+#[cfg(feature = "net")]
 pub mod network;
 pub mod nvme;
 pub mod pci;
 pub mod ramfb;
+pub mod serial;
 pub mod storage;
 pub mod terminal;
 pub mod usb;
 pub mod v4l2;
 pub mod virtio;
 pub mod virtio_gpu;
+#[cfg(feature = "net")]
 pub mod virtio_net;
+pub mod watchdog;
 
 // Phase 8 Wave 2: Networking v2
 pub mod iscsi;
 pub mod raid;
+#[cfg(feature = "net")]
 pub mod wifi;
 
 pub use console::{ConsoleDevice, ConsoleDriver, SerialConsole, VgaConsole};
 pub use gpu::GpuDriver;
+#[cfg(feature = "net")]
 pub use network::{EthernetDriver, LoopbackDriver, NetworkDevice};
 pub use pci::{PciBus, PciDevice};
 pub use storage::{AtaDriver, StorageDevice};
@@ -51,14 +63,18 @@ pub fn init() {
     terminal::init();
 
     // Initialize bus drivers
+    #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+    dt::init();
     pci::init();
     usb::init();
 
     // Initialize device drivers
+    #[cfg(feature = "net")]
     network::init();
     console::init();
     storage::init();
     virtio::blk::init();
+    virtio::rng::init();
     if let Err(_e) = gpu::init() {
         crate::println!("[DRIVERS] Warning: GPU init failed: {:?}", _e);
     }
@@ -76,5 +92,8 @@ pub fn init() {
     // Initialize mouse driver (x86_64: PS/2 aux port, others: stub)
     mouse::init();
 
+    // Look for a hardware watchdog (started later, once boot is done)
+    watchdog::init();
+
     crate::println!("[DRIVERS] Device drivers initialized");
 }
@@ -37,6 +37,23 @@ pub fn cursor_position() -> (i32, i32) {
     )
 }
 
+/// Move the cursor by a relative delta, clamped to the screen bounds.
+pub fn move_cursor(dx: i32, dy: i32) {
+    let cx = CURSOR_X.load(Ordering::Relaxed) + dx;
+    let cy = CURSOR_Y.load(Ordering::Relaxed) + dy;
+    set_cursor_position(cx, cy);
+}
+
+/// Place the cursor at an absolute position, clamped to the screen bounds.
+///
+/// Used by input replay to restore the recorded starting position.
+pub fn set_cursor_position(x: i32, y: i32) {
+    let sw = SCREEN_WIDTH.load(Ordering::Relaxed) as i32;
+    let sh = SCREEN_HEIGHT.load(Ordering::Relaxed) as i32;
+    CURSOR_X.store(x.clamp(0, sw - 1), Ordering::Relaxed);
+    CURSOR_Y.store(y.clamp(0, sh - 1), Ordering::Relaxed);
+}
+
 /// Set screen bounds for cursor clamping.
 pub fn set_screen_bounds(width: u16, height: u16) {
     SCREEN_WIDTH.store(width, Ordering::Relaxed);
@@ -274,15 +291,11 @@ mod x86_64_impl {
             // PS/2 Y axis is inverted (up = positive)
             dy = -dy;
 
-            // Update absolute cursor position
-            let sw = SCREEN_WIDTH.load(Ordering::Relaxed) as i32;
-            let sh = SCREEN_HEIGHT.load(Ordering::Relaxed) as i32;
-            let mut cx = CURSOR_X.load(Ordering::Relaxed) + dx as i32;
-            let mut cy = CURSOR_Y.load(Ordering::Relaxed) + dy as i32;
-            cx = cx.clamp(0, sw - 1);
-            cy = cy.clamp(0, sh - 1);
-            CURSOR_X.store(cx, Ordering::Relaxed);
-            CURSOR_Y.store(cy, Ordering::Relaxed);
+            // Update absolute cursor position (the replayed stream owns
+            // the cursor while an input replay is running)
+            if !crate::drivers::input_replay::is_replaying() {
+                move_cursor(dx as i32, dy as i32);
+            }
 
             let event = MouseEvent { dx, dy, buttons };
             MOUSE_BUFFER.lock().push(event);
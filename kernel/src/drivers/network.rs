@@ -8,7 +8,7 @@ use spin::{Mutex, RwLock};
 
 use crate::{
     error::KernelError,
-    services::driver_framework::{DeviceClass, DeviceInfo, DeviceStatus, Driver},
+    services::driver_framework::{DeviceClass, DeviceInfo, DeviceStatus, Driver, PowerState},
     sync::once_lock::OnceLock,
 };
 
@@ -293,11 +293,11 @@ impl Driver for EthernetDriver {
         Ok(())
     }
 
-    fn suspend(&mut self) -> Result<(), KernelError> {
+    fn suspend(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         self.down()
     }
 
-    fn resume(&mut self) -> Result<(), KernelError> {
+    fn resume(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         self.up()
     }
 
@@ -487,11 +487,11 @@ impl Driver for LoopbackDriver {
         })
     }
 
-    fn suspend(&mut self) -> Result<(), KernelError> {
+    fn suspend(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         self.down()
     }
 
-    fn resume(&mut self) -> Result<(), KernelError> {
+    fn resume(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         self.up()
     }
 
@@ -651,6 +651,8 @@ pub fn init() {
         io_ports: Vec::new(),
         memory_regions: Vec::new(),
         status: DeviceStatus::Uninitialized,
+        parent: None,
+        power: PowerState::On,
     };
 
     let ethernet_driver = EthernetDriver::new(
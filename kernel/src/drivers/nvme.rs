@@ -48,6 +48,9 @@ const ADMIN_SET_FEATURES: u8 = 0x09;
 const IO_READ: u8 = 0x02;
 const IO_WRITE: u8 = 0x01;
 
+/// Force Unit Access bit of a read or write command's CDW12.
+const CDW12_FUA: u32 = 1 << 30;
+
 /// Submission Queue Entry
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -450,11 +453,13 @@ impl NvmeController {
         Ok(())
     }
 
-    /// Write blocks using the first I/O queue.
+    /// Write blocks using the first I/O queue, with Force Unit Access if
+    /// `fua` is set.
     fn write_blocks_internal(
         &mut self,
         start_block: u64,
         buffer: &[u8],
+        fua: bool,
     ) -> Result<(), KernelError> {
         if self.io_queues.is_empty() {
             return Ok(());
@@ -473,6 +478,9 @@ impl NvmeController {
         cmd.cdw10 = (start_block & 0xFFFF_FFFF) as u32;
         cmd.cdw11 = (start_block >> 32) as u32;
         cmd.cdw12 = (num_blocks - 1) as u32;
+        if fua {
+            cmd.cdw12 |= CDW12_FUA;
+        }
 
         // SAFETY: Exclusive access via atomic tail index.
         unsafe {
@@ -544,7 +552,29 @@ impl BlockDevice for NvmeController {
             });
         }
 
-        self.write_blocks_internal(start_block, buffer)
+        self.write_blocks_internal(start_block, buffer, false)
+    }
+
+    fn write_blocks_fua(&mut self, start_block: u64, buffer: &[u8]) -> Result<(), KernelError> {
+        if !buffer.len().is_multiple_of(self.block_size) {
+            return Err(KernelError::InvalidArgument {
+                name: "buffer_length",
+                value: "not_multiple_of_block_size",
+            });
+        }
+
+        self.write_blocks_internal(start_block, buffer, true)
+    }
+
+    fn hw_queues(&self) -> usize {
+        self.io_queues.len().max(1)
+    }
+
+    fn queue_depth(&self) -> usize {
+        // One submission queue slot always stays empty
+        self.io_queues.first().map_or(1, |queue| {
+            (queue.queue_size as usize).saturating_sub(1).max(1)
+        })
     }
 
     fn flush(&mut self) -> Result<(), KernelError> {
@@ -7,7 +7,9 @@ use core::mem;
 
 use crate::{
     error::KernelError,
-    services::driver_framework::{Bus, DeviceClass, DeviceId, DeviceInfo, DeviceStatus},
+    services::driver_framework::{
+        Bus, DeviceClass, DeviceId, DeviceInfo, DeviceStatus, PowerState,
+    },
     sync::once_lock::OnceLock,
 };
 
@@ -590,6 +592,20 @@ impl PciBus {
 
     /// Read configuration dword
     fn read_config_dword(&self, location: PciLocation, offset: u16) -> u32 {
+        // Prefer memory-mapped ECAM when the MCFG covers this bus.
+        #[cfg(target_arch = "x86_64")]
+        if let Some(value) = ecam_base(location.bus).and_then(|base| {
+            ecam_read_config(
+                base,
+                location.bus,
+                location.device,
+                location.function,
+                offset,
+            )
+        }) {
+            return value;
+        }
+
         let address = location.to_config_address() | (offset as u32 & 0xFC);
 
         // SAFETY: PCI configuration space access requires writing the target address
@@ -606,6 +622,20 @@ impl PciBus {
 
     /// Write configuration dword
     fn write_config_dword(&self, location: PciLocation, offset: u16, value: u32) {
+        #[cfg(target_arch = "x86_64")]
+        if let Some(base) = ecam_base(location.bus) {
+            if ecam_write_config(
+                base,
+                location.bus,
+                location.device,
+                location.function,
+                offset,
+                value,
+            ) {
+                return;
+            }
+        }
+
         let address = location.to_config_address() | (offset as u32 & 0xFC);
 
         // SAFETY: PCI configuration space write via mechanism #1. Writing the target
@@ -711,6 +741,8 @@ impl Bus for PciBus {
                 io_ports,
                 memory_regions,
                 status: DeviceStatus::Uninitialized,
+                parent: None,
+                power: PowerState::On,
             };
 
             device_infos.push(device_info);
@@ -871,6 +903,7 @@ pub fn probe_known_drivers() {
                 let _ = crate::drivers::virtio_gpu::init();
             }
             // VirtIO Net (transitional 0x1041, legacy 0x1000)
+            #[cfg(feature = "net")]
             (0x1AF4, 0x1041) | (0x1AF4, 0x1000) => {
                 crate::println!(
                     "  [pci] Probing VirtIO Net ({:#06x}:{:#06x})",
@@ -879,14 +912,16 @@ pub fn probe_known_drivers() {
                 );
                 let _ = crate::drivers::virtio_net::init();
             }
-            // VirtIO Sound (0x1059)
+            // VirtIO Sound (0x1059) is modern-only and needs the virtio 1.0
+            // PCI transport, which is not implemented; audio::init() drives
+            // AC'97 on x86_64 instead
             (0x1AF4, 0x1059) => {
                 crate::println!(
-                    "  [pci] Probing VirtIO Sound ({:#06x}:{:#06x})",
+                    "  [pci] VirtIO Sound ({:#06x}:{:#06x}) needs the modern PCI transport, \
+                     skipping",
                     dev.vendor_id,
                     dev.device_id
                 );
-                let _ = crate::audio::virtio_sound::init();
             }
             // VirtIO Block (transitional 0x1042, legacy 0x1001)
             (0x1AF4, 0x1042) | (0x1AF4, 0x1001) => {
@@ -901,6 +936,102 @@ pub fn probe_known_drivers() {
 // MSI / MSI-X configuration
 // ---------------------------------------------------------------------------
 
+/// Enable I/O space, memory space and bus mastering for a device whose
+/// driver programs DMA itself.
+pub fn enable_bus_master(location: PciLocation) {
+    let bus = get_pci_bus().lock();
+    let offset = PciConfigRegister::Command as u16;
+    // The upper half is the Status register, whose bits are write-1-to-clear:
+    // write it back as zero.
+    let command = bus.read_config_dword(location, offset) & 0xFFFF;
+    let command = command
+        | (command_flags::IO_SPACE | command_flags::MEMORY_SPACE | command_flags::BUS_MASTER)
+            as u32;
+    bus.write_config_dword(location, offset, command);
+}
+
+/// Enable memory space decoding for a device driven only through its MMIO
+/// BARs.
+pub fn enable_memory_space(location: PciLocation) {
+    let bus = get_pci_bus().lock();
+    let offset = PciConfigRegister::Command as u16;
+    let command = bus.read_config_dword(location, offset) & 0xFFFF;
+    bus.write_config_dword(
+        location,
+        offset,
+        command | command_flags::MEMORY_SPACE as u32,
+    );
+}
+
+/// Read the configuration dword at `offset` (rounded down to a multiple of
+/// 4), for device-specific registers past the standard header.
+pub fn read_config(location: PciLocation, offset: u16) -> u32 {
+    get_pci_bus().lock().read_config_dword(location, offset)
+}
+
+/// Write the configuration dword at `offset` (rounded down to a multiple
+/// of 4).
+pub fn write_config(location: PciLocation, offset: u16, value: u32) {
+    get_pci_bus()
+        .lock()
+        .write_config_dword(location, offset, value);
+}
+
+/// Stop a device's DMA and legacy interrupt: clear bus mastering and set
+/// INTx disable. I/O and memory decoding stay on.
+pub fn quiesce(location: PciLocation) {
+    let bus = get_pci_bus().lock();
+    let offset = PciConfigRegister::Command as u16;
+    let command = bus.read_config_dword(location, offset) & 0xFFFF;
+    let command =
+        (command & !(command_flags::BUS_MASTER as u32)) | command_flags::INTERRUPT_DISABLE as u32;
+    bus.write_config_dword(location, offset, command);
+}
+
+/// Command register, BARs and interrupt line of a type 0 header, as saved
+/// by [`save_header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SavedHeader {
+    command: u16,
+    bars: [u32; 6],
+    interrupt_line: u8,
+}
+
+/// Save the part of a device's configuration the kernel programmed.
+pub fn save_header(location: PciLocation) -> SavedHeader {
+    let bus = get_pci_bus().lock();
+    let mut bars = [0; 6];
+    for (i, bar) in bars.iter_mut().enumerate() {
+        *bar = bus.read_config_dword(location, PciConfigRegister::Bar0 as u16 + 4 * i as u16);
+    }
+    SavedHeader {
+        command: bus.read_config_word(location, PciConfigRegister::Command),
+        bars,
+        interrupt_line: bus.read_config_byte(location, PciConfigRegister::InterruptLine),
+    }
+}
+
+/// Write back a header saved by [`save_header`], the command register last
+/// so decoding resumes with the saved BARs.
+pub fn restore_header(location: PciLocation, header: &SavedHeader) {
+    let bus = get_pci_bus().lock();
+    for (i, &bar) in header.bars.iter().enumerate() {
+        bus.write_config_dword(location, PciConfigRegister::Bar0 as u16 + 4 * i as u16, bar);
+    }
+    let offset = PciConfigRegister::InterruptLine as u16;
+    let dword = bus.read_config_dword(location, offset);
+    bus.write_config_dword(
+        location,
+        offset,
+        (dword & !0xFF) | header.interrupt_line as u32,
+    );
+    bus.write_config_dword(
+        location,
+        PciConfigRegister::Command as u16,
+        header.command as u32,
+    );
+}
+
 /// Configure MSI for a device to deliver a specific vector to a target APIC.
 ///
 /// - `location`: PCI device location.
@@ -963,6 +1094,12 @@ pub fn configure_msi(location: PciLocation, msi: &MsiCapability, vector: u8, des
 // PCIe ECAM (Enhanced Configuration Access Mechanism) via MCFG
 // ---------------------------------------------------------------------------
 
+/// ECAM base for `bus` in segment 0, from the ACPI MCFG.
+#[cfg(target_arch = "x86_64")]
+fn ecam_base(bus: u8) -> Option<u64> {
+    crate::arch::x86_64::acpi::with_acpi_info(|info| info.ecam_base(0, bus)).flatten()
+}
+
 /// Read a PCI config register using memory-mapped ECAM access (PCIe).
 ///
 /// `ecam_base` is the physical base address of the ECAM region from ACPI MCFG.
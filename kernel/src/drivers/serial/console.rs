@@ -0,0 +1,225 @@
+//! Console multiplexer
+//!
+//! The console port carries both the kernel log and whatever userspace runs
+//! on the console (a login getty, a shell). Interleaved byte by byte they
+//! garble each other, so all console output goes through [`ConsoleMux`]:
+//! - writers are serialized, so a log line is never split by user output or the
+//!   other way round;
+//! - when the other source left its line unfinished (a prompt, half a log
+//!   line), the new output starts on a fresh line;
+//! - after a log line, the user's unfinished line (the prompt and what has been
+//!   typed so far) is drawn again.
+
+use core::fmt;
+
+use spin::Mutex;
+
+use super::SerialTty;
+
+/// Longest unfinished user line that is redrawn after kernel output.
+const MAX_TAIL: usize = 256;
+
+/// Who is writing to the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Kernel,
+    User,
+}
+
+/// Output state of the console.
+pub struct ConsoleMux {
+    /// The last byte written was not a newline.
+    mid_line: bool,
+    /// Source of the last output.
+    last: Source,
+    /// User output since its last newline.
+    tail: [u8; MAX_TAIL],
+    tail_len: usize,
+    /// `tail` overflowed and is not redrawn.
+    tail_lost: bool,
+}
+
+impl Default for ConsoleMux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleMux {
+    pub const fn new() -> Self {
+        Self {
+            mid_line: false,
+            last: Source::Kernel,
+            tail: [0; MAX_TAIL],
+            tail_len: 0,
+            tail_lost: false,
+        }
+    }
+
+    /// Write `bytes` from `source` to `out`.
+    pub fn write(&mut self, source: Source, bytes: &[u8], out: &mut impl FnMut(u8)) {
+        let Some(&last_byte) = bytes.last() else {
+            return;
+        };
+        if source != self.last && self.mid_line {
+            out(b'\r');
+            out(b'\n');
+        }
+        bytes.iter().for_each(|&byte| out(byte));
+        self.mid_line = last_byte != b'\n';
+        self.last = source;
+
+        if source == Source::User {
+            for &byte in bytes {
+                self.track(byte);
+            }
+        }
+    }
+
+    /// A kernel message is complete: draw the user's unfinished line again
+    /// below it.
+    pub fn end_kernel(&mut self, out: &mut impl FnMut(u8)) {
+        if self.last != Source::Kernel || self.mid_line || self.tail_len == 0 || self.tail_lost {
+            return;
+        }
+        self.tail[..self.tail_len]
+            .iter()
+            .for_each(|&byte| out(byte));
+        self.mid_line = true;
+        self.last = Source::User;
+    }
+
+    /// Follow user output to know its unfinished line.
+    fn track(&mut self, byte: u8) {
+        match byte {
+            b'\n' | b'\r' => {
+                self.tail_len = 0;
+                self.tail_lost = false;
+            }
+            0x08 => self.tail_len = self.tail_len.saturating_sub(1),
+            _ if self.tail_len < MAX_TAIL => {
+                self.tail[self.tail_len] = byte;
+                self.tail_len += 1;
+            }
+            _ => self.tail_lost = true,
+        }
+    }
+}
+
+static MUX: Mutex<ConsoleMux> = Mutex::new(ConsoleMux::new());
+
+/// Write userspace output to the console port `tty`.
+pub(super) fn write_user(tty: &SerialTty, data: &[u8], onlcr: bool) {
+    let _irq = crate::arch::disable_interrupts();
+    let mut mux = MUX.lock();
+    let mut uart = tty.uart.lock();
+    let mut out = |byte| uart.write_byte(byte);
+    for chunk in data.split_inclusive(|&byte| byte == b'\n') {
+        match chunk.split_last() {
+            Some((b'\n', line)) if onlcr => {
+                mux.write(Source::User, line, &mut out);
+                mux.write(Source::User, b"\r\n", &mut out);
+            }
+            _ => mux.write(Source::User, chunk, &mut out),
+        }
+    }
+}
+
+/// Formats kernel output into the multiplexer.
+struct KernelWriter<'a, F: FnMut(u8)> {
+    mux: &'a mut ConsoleMux,
+    out: &'a mut F,
+}
+
+impl<F: FnMut(u8)> fmt::Write for KernelWriter<'_, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.mux.write(Source::Kernel, s.as_bytes(), self.out);
+        Ok(())
+    }
+}
+
+/// Write kernel log output to the console port.
+///
+/// Returns `false` before the console port is set up, for the caller to
+/// use the early console instead.
+pub fn print(args: fmt::Arguments) -> bool {
+    use fmt::Write;
+
+    let Some(tty) = super::console() else {
+        return false;
+    };
+    let _irq = crate::arch::disable_interrupts();
+    let mut mux = MUX.lock();
+    let mut uart = tty.uart.lock();
+    let mut out = |byte| uart.write_byte(byte);
+    let _ = KernelWriter {
+        mux: &mut mux,
+        out: &mut out,
+    }
+    .write_fmt(args);
+    mux.end_kernel(&mut out);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn run(mux: &mut ConsoleMux, writes: &[(Source, &[u8])]) -> Vec<u8> {
+        let mut screen = Vec::new();
+        let mut out = |byte| screen.push(byte);
+        for &(source, bytes) in writes {
+            mux.write(source, bytes, &mut out);
+            if source == Source::Kernel {
+                mux.end_kernel(&mut out);
+            }
+        }
+        screen
+    }
+
+    #[test]
+    fn test_kernel_line_breaks_and_redraws_prompt() {
+        let mut mux = ConsoleMux::new();
+        let screen = run(
+            &mut mux,
+            &[
+                (Source::User, b"login: "),
+                (Source::User, b"ro"),
+                (Source::Kernel, b"[NET] link up\n"),
+            ],
+        );
+        assert_eq!(screen, b"login: ro\r\n[NET] link up\nlogin: ro");
+    }
+
+    #[test]
+    fn test_user_output_after_partial_kernel_line() {
+        let mut mux = ConsoleMux::new();
+        let screen = run(
+            &mut mux,
+            &[(Source::Kernel, b"[BOOT] "), (Source::User, b"$ ")],
+        );
+        assert_eq!(screen, b"[BOOT] \r\n$ ");
+    }
+
+    #[test]
+    fn test_no_redraw_after_complete_user_line() {
+        let mut mux = ConsoleMux::new();
+        let screen = run(
+            &mut mux,
+            &[(Source::User, b"ok\r\n"), (Source::Kernel, b"msg\n")],
+        );
+        assert_eq!(screen, b"ok\r\nmsg\n");
+    }
+
+    #[test]
+    fn test_erase_is_tracked() {
+        let mut mux = ConsoleMux::new();
+        let screen = run(
+            &mut mux,
+            &[(Source::User, b"$ lz\x08 \x08s"), (Source::Kernel, b"x\n")],
+        );
+        assert!(screen.ends_with(b"x\n$ ls"));
+    }
+}
@@ -0,0 +1,305 @@
+//! Serial Ports
+//!
+//! UART drivers behind `/dev/ttyS*`:
+//! - [`ns16550`]: the 16550 family, on I/O ports on x86_64 (COM1-COM4) and
+//!   memory-mapped on RISC-V
+//! - [`pl011`]: the ARM PrimeCell UART on AArch64
+//!
+//! Received bytes are moved from the UART into a ring buffer per port by the
+//! receive interrupt where one is routed (IRQ 4 and 3 on x86_64) and by the
+//! timer tick everywhere, so input is not lost while nobody is reading. Each
+//! port has its own termios settings and line discipline ([`tty`]).
+//!
+//! `ttyS0` is the system console: it shares its termios with
+//! `/dev/console`, and its output goes through the [`console`] multiplexer
+//! together with the kernel log.
+
+pub mod console;
+pub mod ns16550;
+pub mod pl011;
+pub mod tty;
+
+use alloc::{boxed::Box, format, sync::Arc, vec::Vec};
+
+use spin::RwLock;
+
+pub use self::tty::SerialTty;
+use super::terminal::{KernelTermios, CSIZE, CSTOPB, PARENB, PARODD};
+
+/// Major device number of `/dev/ttyS*`; minors start at [`MINOR_BASE`].
+pub const TTYS_MAJOR: u32 = 4;
+/// Minor device number of `/dev/ttyS0`.
+pub const MINOR_BASE: u32 = 64;
+
+/// termios `c_cflag` baud rate field.
+const CBAUD: u32 = 0o010017;
+
+/// Baud rate codes of the termios `CBAUD` field.
+const BAUD_CODES: &[(u32, u32)] = &[
+    (0o000011, 1200),
+    (0o000013, 2400),
+    (0o000014, 4800),
+    (0o000015, 9600),
+    (0o000016, 19200),
+    (0o000017, 38400),
+    (0o010001, 57600),
+    (0o010002, 115200),
+    (0o010003, 230400),
+    (0o010004, 460800),
+    (0o010007, 921600),
+];
+
+/// A UART as the tty layer drives it.
+pub trait Uart: Send {
+    /// Enable the FIFOs, transmitter and receiver, keeping the line
+    /// settings the firmware left.
+    fn init(&mut self);
+
+    /// Program the line settings.
+    fn configure(&mut self, line: LineConfig);
+
+    /// Take one received byte, if there is one.
+    fn try_read(&mut self) -> Option<u8>;
+
+    /// Transmit one byte, waiting for room in the transmitter.
+    fn write_byte(&mut self, byte: u8);
+
+    /// Enable or disable the receive interrupt.
+    fn set_rx_interrupt(&mut self, enabled: bool);
+}
+
+/// Parity bit setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+/// Line settings of a UART.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineConfig {
+    /// Bits per second; 0 leaves the divisor alone.
+    pub baud: u32,
+    /// Data bits per character, 5 to 8.
+    pub data_bits: u8,
+    pub two_stop_bits: bool,
+    pub parity: Parity,
+}
+
+impl LineConfig {
+    /// Line settings described by `termios`.
+    ///
+    /// The rate comes from the `CBAUD` field of `c_cflag` as the C library
+    /// sets it, or from `c_ospeed` when that field is clear.
+    pub fn from_termios(termios: &KernelTermios) -> Self {
+        let code = termios.c_cflag & CBAUD;
+        let baud = if code == 0 {
+            termios.c_ospeed
+        } else {
+            BAUD_CODES
+                .iter()
+                .find(|&&(c, _)| c == code)
+                .map_or(0, |&(_, baud)| baud)
+        };
+        let parity = match (termios.c_cflag & PARENB != 0, termios.c_cflag & PARODD != 0) {
+            (false, _) => Parity::None,
+            (true, false) => Parity::Even,
+            (true, true) => Parity::Odd,
+        };
+        Self {
+            baud,
+            data_bits: 5 + ((termios.c_cflag & CSIZE) >> 4) as u8,
+            two_stop_bits: termios.c_cflag & CSTOPB != 0,
+            parity,
+        }
+    }
+}
+
+/// Registered ports; index `n` is `ttyS<n>`.
+static PORTS: RwLock<Vec<Arc<SerialTty>>> = RwLock::new(Vec::new());
+
+/// Look up a port by name, e.g. `ttyS1`.
+pub fn get(name: &str) -> Option<Arc<SerialTty>> {
+    let index: usize = name.strip_prefix("ttyS")?.parse().ok()?;
+    PORTS.read().get(index).cloned()
+}
+
+/// The console port, `ttyS0`.
+pub fn console() -> Option<Arc<SerialTty>> {
+    PORTS.read().first().cloned()
+}
+
+/// Number of registered ports.
+pub fn port_count() -> usize {
+    PORTS.read().len()
+}
+
+/// Move received bytes of every port into its ring buffer.
+///
+/// Called from the timer tick and the UART interrupt, so it never waits for
+/// a lock: a port that is busy is drained on the next call.
+pub fn poll() {
+    if let Some(ports) = PORTS.try_read() {
+        for port in ports.iter() {
+            port.poll();
+        }
+    }
+}
+
+/// UART receive interrupt.
+pub fn handle_interrupt() {
+    poll();
+}
+
+/// Take one byte of raw console input, if there is one.
+///
+/// For the kernel shell, which does its own line editing; the console
+/// line discipline is bypassed.
+pub fn read_console_byte() -> Option<u8> {
+    let tty = console()?;
+    tty.poll();
+    tty.read_byte()
+}
+
+/// Read from the console with its line discipline. Returns 0 before the
+/// console port is set up.
+pub fn read_console(buffer: &mut [u8]) -> usize {
+    console().map_or(0, |tty| tty.read(buffer))
+}
+
+/// Whether a console read would return without waiting.
+pub fn console_input_ready() -> bool {
+    console().is_some_and(|tty| tty.input_ready())
+}
+
+/// Write userspace output to the console, or to the early console before
+/// the console port is set up.
+pub fn write_console(data: &[u8]) {
+    match console() {
+        Some(tty) => tty.write(data),
+        None => {
+            for &byte in data {
+                crate::serial::_serial_print(format_args!("{}", byte as char));
+            }
+        }
+    }
+}
+
+/// Discard console input that has not been read (`TCSETSF`, `TCFLSH`).
+pub fn flush_console_input() {
+    if let Some(tty) = console() {
+        tty.flush_input();
+    }
+}
+
+/// The console termios changed from `old` to `new`; reprogram the console
+/// UART if the line settings differ.
+pub fn console_termios_changed(old: &KernelTermios, new: &KernelTermios) {
+    if let Some(tty) = console() {
+        tty.apply_line(old, new);
+    }
+}
+
+/// UARTs present on this machine, with their ISA IRQ where one is routable.
+#[cfg(target_arch = "x86_64")]
+fn probe() -> Vec<(Box<dyn Uart>, Option<u8>)> {
+    const COM_PORTS: [(u16, u8); 4] = [(0x3F8, 4), (0x2F8, 3), (0x3E8, 4), (0x2E8, 3)];
+
+    let mut uarts: Vec<(Box<dyn Uart>, Option<u8>)> = Vec::new();
+    for (base, irq) in COM_PORTS {
+        let mut uart = ns16550::Ns16550::port(base);
+        if uart.probe() {
+            uarts.push((Box::new(uart), Some(irq)));
+        }
+    }
+    uarts
+}
+
+/// UARTs present on this machine, with their ISA IRQ where one is routable.
+#[cfg(target_arch = "aarch64")]
+fn probe() -> Vec<(Box<dyn Uart>, Option<u8>)> {
+    let uart = pl011::Pl011::new(crate::arch::aarch64::serial::base());
+    alloc::vec![(Box::new(uart) as Box<dyn Uart>, None)]
+}
+
+/// UARTs present on this machine, with their ISA IRQ where one is routable.
+#[cfg(target_arch = "riscv64")]
+fn probe() -> Vec<(Box<dyn Uart>, Option<u8>)> {
+    let base = match super::dt::console_uart() {
+        Some((base, "ns16550a" | "ns16550")) => base,
+        _ => ns16550::RISCV_VIRT_BASE,
+    };
+    let uart = ns16550::Ns16550::mmio(base, 0, ns16550::RISCV_VIRT_CLOCK_HZ);
+    alloc::vec![(Box::new(uart) as Box<dyn Uart>, None)]
+}
+
+/// Route the ISA `irq` of a port to its interrupt handler.
+#[cfg(target_arch = "x86_64")]
+fn route_irq(irq: u8) -> bool {
+    use crate::arch::x86_64::apic;
+    let dest = apic::read_id().unwrap_or(0);
+    apic::set_irq_route(irq, 32 + irq, dest).is_ok()
+}
+
+/// Route the ISA `irq` of a port to its interrupt handler.
+#[cfg(not(target_arch = "x86_64"))]
+fn route_irq(_irq: u8) -> bool {
+    false
+}
+
+/// Find the UARTs and create `/dev/ttyS*` for them.
+///
+/// Ports without a routed interrupt are drained from the timer tick only.
+pub fn init() {
+    // ttyS0 keeps its settings in the console terminal state.
+    super::terminal::init();
+
+    let mut ports = PORTS.write();
+    if !ports.is_empty() {
+        return;
+    }
+    for (index, (mut uart, irq)) in probe().into_iter().enumerate() {
+        uart.init();
+        let irq = irq.filter(|&irq| route_irq(irq));
+        uart.set_rx_interrupt(irq.is_some());
+
+        let name = format!("ttyS{}", index);
+        crate::fs::devfs::register_char_device(&name, TTYS_MAJOR, MINOR_BASE + index as u32);
+        match irq {
+            Some(_irq) => crate::println!("[SERIAL] {}: IRQ {}", name, _irq),
+            None => crate::println!("[SERIAL] {}: polled", name),
+        }
+        ports.push(Arc::new(SerialTty::new(name, index, uart)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::terminal::{CS7, CS8};
+
+    #[test]
+    fn test_line_config_from_termios() {
+        let mut termios = KernelTermios::default_console();
+        assert_eq!(
+            LineConfig::from_termios(&termios),
+            LineConfig {
+                baud: 38400,
+                data_bits: 8,
+                two_stop_bits: false,
+                parity: Parity::None,
+            }
+        );
+
+        termios.c_cflag = (termios.c_cflag & !CSIZE) | CS7 | PARENB | PARODD | CSTOPB | 0o010002;
+        let line = LineConfig::from_termios(&termios);
+        assert_eq!(line.baud, 115200);
+        assert_eq!(line.data_bits, 7);
+        assert!(line.two_stop_bits);
+        assert_eq!(line.parity, Parity::Odd);
+
+        termios.c_cflag = CS8 | PARENB;
+        assert_eq!(LineConfig::from_termios(&termios).parity, Parity::Even);
+    }
+}
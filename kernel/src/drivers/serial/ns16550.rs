@@ -0,0 +1,172 @@
+//! NS16550 UART
+//!
+//! The 16550 and compatibles: the PC COM ports on x86_64 I/O ports, and the
+//! memory-mapped `ns16550a` of RISC-V boards (registers `1 << shift` bytes
+//! apart).
+
+use super::{LineConfig, Parity, Uart};
+
+/// Base of the UART on QEMU's RISC-V `virt` machine.
+pub const RISCV_VIRT_BASE: usize = 0x1000_0000;
+/// Input clock of the UART on QEMU's RISC-V `virt` machine.
+pub const RISCV_VIRT_CLOCK_HZ: u32 = 3_686_400;
+/// Input clock of a PC COM port.
+pub const PC_CLOCK_HZ: u32 = 1_843_200;
+
+// Register offsets
+const RBR_THR_DLL: u16 = 0;
+const IER_DLM: u16 = 1;
+const FCR: u16 = 2;
+const LCR: u16 = 3;
+const MCR: u16 = 4;
+const LSR: u16 = 5;
+const SCR: u16 = 7;
+
+const IER_RX_AVAILABLE: u8 = 0x01;
+/// Enable and clear both FIFOs, receive trigger at 14 bytes.
+const FCR_ENABLE_14: u8 = 0xC7;
+const LCR_DLAB: u8 = 0x80;
+/// DTR, RTS and OUT2, which gates the interrupt line on PCs.
+const MCR_DTR_RTS_OUT2: u8 = 0x0B;
+const LSR_DATA_READY: u8 = 0x01;
+const LSR_THR_EMPTY: u8 = 0x20;
+
+enum Regs {
+    #[cfg(target_arch = "x86_64")]
+    Port(u16),
+    Mmio {
+        base: usize,
+        shift: u32,
+    },
+}
+
+/// A 16550-compatible UART.
+pub struct Ns16550 {
+    regs: Regs,
+    clock_hz: u32,
+}
+
+impl Ns16550 {
+    /// The UART at I/O port `base`.
+    #[cfg(target_arch = "x86_64")]
+    pub const fn port(base: u16) -> Self {
+        Self {
+            regs: Regs::Port(base),
+            clock_hz: PC_CLOCK_HZ,
+        }
+    }
+
+    /// The UART mapped at `base`, registers `1 << shift` bytes apart.
+    pub const fn mmio(base: usize, shift: u32, clock_hz: u32) -> Self {
+        Self {
+            regs: Regs::Mmio { base, shift },
+            clock_hz,
+        }
+    }
+
+    fn read(&self, reg: u16) -> u8 {
+        match self.regs {
+            // SAFETY: `base` is the I/O port block of a 16550; reading one of
+            // its eight registers has no effect beyond the UART.
+            #[cfg(target_arch = "x86_64")]
+            Regs::Port(base) => unsafe {
+                x86_64::instructions::port::Port::<u8>::new(base + reg).read()
+            },
+            // SAFETY: `base` is the MMIO block of a 16550 (from the device
+            // tree or the platform's fixed map), identity-mapped, and the
+            // register offset stays inside it.
+            Regs::Mmio { base, shift } => unsafe {
+                core::ptr::read_volatile((base + ((reg as usize) << shift)) as *const u8)
+            },
+        }
+    }
+
+    fn write(&self, reg: u16, value: u8) {
+        match self.regs {
+            // SAFETY: As in `read`; writes only program the UART.
+            #[cfg(target_arch = "x86_64")]
+            Regs::Port(base) => unsafe {
+                x86_64::instructions::port::Port::<u8>::new(base + reg).write(value)
+            },
+            // SAFETY: As in `read`.
+            Regs::Mmio { base, shift } => unsafe {
+                core::ptr::write_volatile((base + ((reg as usize) << shift)) as *mut u8, value)
+            },
+        }
+    }
+
+    /// Check through the scratch register that a UART is present.
+    pub fn probe(&mut self) -> bool {
+        [0x5A, 0xA5].iter().all(|&pattern| {
+            self.write(SCR, pattern);
+            self.read(SCR) == pattern
+        })
+    }
+}
+
+/// Line control register value for `line`.
+fn lcr_bits(line: &LineConfig) -> u8 {
+    let mut lcr = line.data_bits.clamp(5, 8) - 5;
+    if line.two_stop_bits {
+        lcr |= 0x04;
+    }
+    lcr |= match line.parity {
+        Parity::None => 0,
+        Parity::Odd => 0x08,
+        Parity::Even => 0x18,
+    };
+    lcr
+}
+
+impl Uart for Ns16550 {
+    fn init(&mut self) {
+        self.write(FCR, FCR_ENABLE_14);
+        self.write(MCR, MCR_DTR_RTS_OUT2);
+    }
+
+    fn configure(&mut self, line: LineConfig) {
+        let lcr = lcr_bits(&line);
+        if line.baud != 0 {
+            let divisor = (self.clock_hz / (16 * line.baud)).clamp(1, u16::MAX as u32) as u16;
+            self.write(LCR, LCR_DLAB);
+            self.write(RBR_THR_DLL, divisor as u8);
+            self.write(IER_DLM, (divisor >> 8) as u8);
+        }
+        self.write(LCR, lcr);
+    }
+
+    fn try_read(&mut self) -> Option<u8> {
+        (self.read(LSR) & LSR_DATA_READY != 0).then(|| self.read(RBR_THR_DLL))
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        while self.read(LSR) & LSR_THR_EMPTY == 0 {
+            core::hint::spin_loop();
+        }
+        self.write(RBR_THR_DLL, byte);
+    }
+
+    fn set_rx_interrupt(&mut self, enabled: bool) {
+        self.write(IER_DLM, if enabled { IER_RX_AVAILABLE } else { 0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lcr_bits() {
+        let mut line = LineConfig {
+            baud: 38400,
+            data_bits: 8,
+            two_stop_bits: false,
+            parity: Parity::None,
+        };
+        assert_eq!(lcr_bits(&line), 0x03);
+        line.data_bits = 7;
+        line.parity = Parity::Even;
+        line.two_stop_bits = true;
+        assert_eq!(lcr_bits(&line), 0x1E);
+    }
+}
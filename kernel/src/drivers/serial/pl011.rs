@@ -0,0 +1,147 @@
+//! ARM PL011 UART
+
+use super::{LineConfig, Parity, Uart};
+
+/// Reference clock of the PL011 on QEMU's `virt` machine.
+pub const QEMU_VIRT_CLOCK_HZ: u32 = 24_000_000;
+
+// Register offsets
+const DR: usize = 0x00;
+const FR: usize = 0x18;
+const IBRD: usize = 0x24;
+const FBRD: usize = 0x28;
+const LCR_H: usize = 0x2C;
+const CR: usize = 0x30;
+const IMSC: usize = 0x38;
+
+const FR_BUSY: u32 = 1 << 3;
+const FR_RXFE: u32 = 1 << 4;
+const FR_TXFF: u32 = 1 << 5;
+const LCR_H_PEN: u32 = 1 << 1;
+const LCR_H_EPS: u32 = 1 << 2;
+const LCR_H_STP2: u32 = 1 << 3;
+const LCR_H_FEN: u32 = 1 << 4;
+const CR_UARTEN: u32 = 1 << 0;
+const CR_TXE: u32 = 1 << 8;
+const CR_RXE: u32 = 1 << 9;
+/// Receive and receive-timeout interrupts.
+const IMSC_RX: u32 = (1 << 4) | (1 << 6);
+
+/// A PL011 UART.
+pub struct Pl011 {
+    base: usize,
+    clock_hz: u32,
+}
+
+impl Pl011 {
+    /// The UART mapped at `base`.
+    pub const fn new(base: usize) -> Self {
+        Self {
+            base,
+            clock_hz: QEMU_VIRT_CLOCK_HZ,
+        }
+    }
+
+    fn read(&self, reg: usize) -> u32 {
+        // SAFETY: `base` is the PL011 from the device tree (or QEMU's fixed
+        // address), identity-mapped, and `reg` is one of its registers.
+        unsafe { core::ptr::read_volatile((self.base + reg) as *const u32) }
+    }
+
+    fn write(&self, reg: usize, value: u32) {
+        // SAFETY: As in `read`; writes only program the UART.
+        unsafe { core::ptr::write_volatile((self.base + reg) as *mut u32, value) }
+    }
+}
+
+/// Line control register value for `line`, with the FIFOs enabled.
+fn lcr_h_bits(line: &LineConfig) -> u32 {
+    let mut lcr_h = LCR_H_FEN | ((line.data_bits.clamp(5, 8) as u32 - 5) << 5);
+    if line.two_stop_bits {
+        lcr_h |= LCR_H_STP2;
+    }
+    lcr_h |= match line.parity {
+        Parity::None => 0,
+        Parity::Odd => LCR_H_PEN,
+        Parity::Even => LCR_H_PEN | LCR_H_EPS,
+    };
+    lcr_h
+}
+
+/// Integer and fractional (1/64) baud rate divisors.
+fn divisors(clock_hz: u32, baud: u32) -> (u32, u32) {
+    let div = ((clock_hz as u64 * 4 + baud as u64 / 2) / baud as u64) as u32;
+    (div >> 6, div & 0x3F)
+}
+
+impl Uart for Pl011 {
+    fn init(&mut self) {
+        self.write(CR, self.read(CR) | CR_UARTEN | CR_TXE | CR_RXE);
+    }
+
+    fn configure(&mut self, line: LineConfig) {
+        // The UART must be disabled and idle while it is reprogrammed.
+        while self.read(FR) & FR_BUSY != 0 {
+            core::hint::spin_loop();
+        }
+        let cr = self.read(CR);
+        self.write(CR, 0);
+        if line.baud != 0 {
+            let (ibrd, fbrd) = divisors(self.clock_hz, line.baud);
+            self.write(IBRD, ibrd);
+            self.write(FBRD, fbrd);
+        }
+        // LCR_H latches the divisors, so it goes last.
+        self.write(LCR_H, lcr_h_bits(&line));
+        self.write(CR, cr);
+    }
+
+    fn try_read(&mut self) -> Option<u8> {
+        (self.read(FR) & FR_RXFE == 0).then(|| self.read(DR) as u8)
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        while self.read(FR) & FR_TXFF != 0 {
+            core::hint::spin_loop();
+        }
+        self.write(DR, byte as u32);
+    }
+
+    fn set_rx_interrupt(&mut self, enabled: bool) {
+        let imsc = self.read(IMSC);
+        self.write(
+            IMSC,
+            if enabled {
+                imsc | IMSC_RX
+            } else {
+                imsc & !IMSC_RX
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divisors() {
+        // 24 MHz / (16 * 115200) = 13.02 -> 13 + 1/64
+        assert_eq!(divisors(24_000_000, 115_200), (13, 1));
+        assert_eq!(divisors(24_000_000, 38_400), (39, 4));
+    }
+
+    #[test]
+    fn test_lcr_h_bits() {
+        let line = LineConfig {
+            baud: 0,
+            data_bits: 8,
+            two_stop_bits: false,
+            parity: Parity::Even,
+        };
+        assert_eq!(
+            lcr_h_bits(&line),
+            LCR_H_FEN | (3 << 5) | LCR_H_PEN | LCR_H_EPS
+        );
+    }
+}
@@ -0,0 +1,501 @@
+//! Serial tty line discipline
+//!
+//! A [`SerialTty`] owns one UART and the input received on it. Reads apply
+//! the port's termios the way the N_TTY line discipline does:
+//! - canonical mode (`ICANON`) collects a line with `VERASE`/`VKILL` editing
+//!   and hands it out once `\n` or `VEOF` ends it;
+//! - raw mode returns bytes as they arrive, per `VMIN` and `VTIME`;
+//! - `ICRNL`, `INLCR` and `IGNCR` map input, `ECHO`/`ECHOE`/`ECHOK` echo it and
+//!   `OPOST`+`ONLCR` turn `\n` into `\r\n` on output.
+//!
+//! Signal characters (`ISIG`) are passed through as data.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spin::Mutex;
+
+use super::{console, LineConfig, Uart};
+use crate::drivers::terminal::{
+    self, KernelTermios, ECHOE, ECHOK, ICRNL, IGNCR, INLCR, ONLCR, OPOST, VEOF, VERASE, VKILL,
+};
+
+/// Received bytes buffered per port.
+const RX_RING_SIZE: usize = 1024;
+
+/// Longest canonical input line; further bytes up to the newline are dropped.
+const MAX_LINE: usize = 4096;
+
+/// Echo of an erased character.
+const ERASE_ECHO: &[u8] = b"\x08 \x08";
+
+/// Fixed-size FIFO of received bytes.
+pub(super) struct RxRing {
+    buf: [u8; RX_RING_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl RxRing {
+    pub(super) const fn new() -> Self {
+        Self {
+            buf: [0; RX_RING_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Append a byte; `false` if the ring is full.
+    pub(super) fn push(&mut self, byte: u8) -> bool {
+        if self.len == RX_RING_SIZE {
+            return false;
+        }
+        self.buf[(self.head + self.len) % RX_RING_SIZE] = byte;
+        self.len += 1;
+        true
+    }
+
+    pub(super) fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buf[self.head];
+        self.head = (self.head + 1) % RX_RING_SIZE;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+}
+
+/// Apply the termios input mapping to `byte`; `None` if it is dropped.
+fn map_input(byte: u8, termios: &KernelTermios) -> Option<u8> {
+    match byte {
+        b'\r' if termios.c_iflag & IGNCR != 0 => None,
+        b'\r' if termios.c_iflag & ICRNL != 0 => Some(b'\n'),
+        b'\n' if termios.c_iflag & INLCR != 0 => Some(b'\r'),
+        _ => Some(byte),
+    }
+}
+
+/// Whether `byte` is the control character at `index` (0 disables one).
+fn is_cc(byte: u8, termios: &KernelTermios, index: usize) -> bool {
+    let cc = termios.c_cc[index];
+    cc != 0 && byte == cc
+}
+
+/// Feed one input byte to the canonical line being edited.
+///
+/// What the terminal should show is appended to `echo`. Returns `true`
+/// once the line is complete; `VEOF` completes it without being stored, so
+/// an empty completed line means end of file.
+fn edit_line(line: &mut Vec<u8>, byte: u8, termios: &KernelTermios, echo: &mut Vec<u8>) -> bool {
+    let echoing = termios.is_echo();
+    if is_cc(byte, termios, VERASE) || byte == 0x08 {
+        if line.pop().is_some() && echoing && termios.c_lflag & ECHOE != 0 {
+            echo.extend_from_slice(ERASE_ECHO);
+        }
+        return false;
+    }
+    if is_cc(byte, termios, VKILL) {
+        if echoing && termios.c_lflag & ECHOK != 0 {
+            if termios.c_lflag & ECHOE != 0 {
+                for _ in 0..line.len() {
+                    echo.extend_from_slice(ERASE_ECHO);
+                }
+            } else {
+                echo.push(b'\n');
+            }
+        }
+        line.clear();
+        return false;
+    }
+    if is_cc(byte, termios, VEOF) {
+        return true;
+    }
+
+    if line.len() < MAX_LINE || byte == b'\n' {
+        line.push(byte);
+        if echoing {
+            echo.push(byte);
+        }
+    }
+    byte == b'\n'
+}
+
+/// A serial port as a terminal.
+pub struct SerialTty {
+    name: String,
+    index: usize,
+    pub(super) uart: Mutex<Box<dyn Uart>>,
+    rx: Mutex<RxRing>,
+    /// Settings of ports other than the console, which uses the console
+    /// terminal state.
+    termios: Mutex<KernelTermios>,
+    /// Canonical line still being edited.
+    line: Mutex<Vec<u8>>,
+    /// Completed canonical line not yet read in full; empty means end of
+    /// file.
+    pending: Mutex<Option<Vec<u8>>>,
+    /// Bytes dropped because the ring was full.
+    overruns: AtomicU64,
+}
+
+impl SerialTty {
+    pub(super) fn new(name: String, index: usize, uart: Box<dyn Uart>) -> Self {
+        Self {
+            name,
+            index,
+            uart: Mutex::new(uart),
+            rx: Mutex::new(RxRing::new()),
+            termios: Mutex::new(KernelTermios::default_console()),
+            line: Mutex::new(Vec::new()),
+            pending: Mutex::new(None),
+            overruns: AtomicU64::new(0),
+        }
+    }
+
+    /// Device name, e.g. `ttyS0`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this is the console port.
+    pub fn is_console(&self) -> bool {
+        self.index == 0
+    }
+
+    /// Received bytes dropped because nobody read them in time.
+    pub fn overruns(&self) -> u64 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Current termios settings.
+    pub fn termios(&self) -> KernelTermios {
+        if self.is_console() {
+            terminal::get_termios_snapshot()
+        } else {
+            *self.termios.lock()
+        }
+    }
+
+    /// Change the termios settings, reprogramming the UART if the line
+    /// settings changed.
+    pub fn set_termios(&self, new: &KernelTermios) {
+        if self.is_console() {
+            // Comes back through `console_termios_changed`.
+            terminal::set_termios(new);
+            return;
+        }
+        let old = core::mem::replace(&mut *self.termios.lock(), *new);
+        self.apply_line(&old, new);
+    }
+
+    pub(super) fn apply_line(&self, old: &KernelTermios, new: &KernelTermios) {
+        let line = LineConfig::from_termios(new);
+        if line != LineConfig::from_termios(old) {
+            let _irq = crate::arch::disable_interrupts();
+            self.uart.lock().configure(line);
+        }
+    }
+
+    /// Discard received input that has not been read.
+    pub fn flush_input(&self) {
+        self.rx.lock().clear();
+        self.line.lock().clear();
+        *self.pending.lock() = None;
+    }
+
+    /// Move bytes from the UART into the ring buffer, unless either is in
+    /// use.
+    pub(super) fn poll(&self) {
+        let (Some(mut uart), Some(mut rx)) = (self.uart.try_lock(), self.rx.try_lock()) else {
+            return;
+        };
+        while let Some(byte) = uart.try_read() {
+            if !rx.push(byte) {
+                self.overruns.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Take one raw byte from the ring buffer.
+    pub(super) fn read_byte(&self) -> Option<u8> {
+        self.rx.lock().pop()
+    }
+
+    /// Next input byte after input mapping, if one has arrived.
+    fn next_input(&self, termios: &KernelTermios) -> Option<u8> {
+        self.poll();
+        loop {
+            let byte = self.read_byte()?;
+            if let Some(byte) = map_input(byte, termios) {
+                return Some(byte);
+            }
+        }
+    }
+
+    /// Read with the line discipline; blocks as the termios settings say.
+    pub fn read(&self, buffer: &mut [u8]) -> usize {
+        if buffer.is_empty() {
+            return 0;
+        }
+        let termios = self.termios();
+        if termios.is_canonical() {
+            self.read_canonical(buffer, &termios)
+        } else {
+            self.read_raw(buffer, &termios)
+        }
+    }
+
+    /// Edit arrived input into the canonical line until it is complete,
+    /// waiting for more input only if `wait`. Returns whether a completed
+    /// line is in `pending`.
+    fn cook_line(
+        &self,
+        pending: &mut Option<Vec<u8>>,
+        termios: &KernelTermios,
+        wait: bool,
+    ) -> bool {
+        if pending.is_some() {
+            return true;
+        }
+        let mut line = self.line.lock();
+        let mut echo = Vec::new();
+        loop {
+            let Some(byte) = self.next_input(termios) else {
+                if !wait {
+                    return false;
+                }
+                core::hint::spin_loop();
+                continue;
+            };
+            let done = edit_line(&mut line, byte, termios, &mut echo);
+            if !echo.is_empty() {
+                self.write(&echo);
+                echo.clear();
+            }
+            if done {
+                *pending = Some(core::mem::take(&mut *line));
+                return true;
+            }
+        }
+    }
+
+    fn read_canonical(&self, buffer: &mut [u8], termios: &KernelTermios) -> usize {
+        let mut pending = self.pending.lock();
+        self.cook_line(&mut pending, termios, true);
+        let Some(line) = pending.as_mut() else {
+            return 0;
+        };
+        let n = line.len().min(buffer.len());
+        buffer[..n].copy_from_slice(&line[..n]);
+        line.drain(..n);
+        if line.is_empty() {
+            *pending = None;
+        }
+        n
+    }
+
+    fn read_raw(&self, buffer: &mut [u8], termios: &KernelTermios) -> usize {
+        let vmin = termios.vmin() as usize;
+        let vtime_ms = termios.vtime() as u64 * 100;
+        let target = vmin.min(buffer.len());
+        let now = crate::arch::timer::get_timestamp_ms;
+        // VTIME is an overall timeout without VMIN, else an inter-byte one
+        // started by the first byte.
+        let mut deadline = (vmin == 0 && vtime_ms > 0).then(|| now() + vtime_ms);
+
+        let mut n = 0;
+        loop {
+            while n < buffer.len() {
+                let Some(byte) = self.next_input(termios) else {
+                    break;
+                };
+                buffer[n] = byte;
+                n += 1;
+                if termios.is_echo() {
+                    self.write(&[byte]);
+                }
+                if vtime_ms > 0 {
+                    deadline = Some(now() + vtime_ms);
+                }
+            }
+            if n > 0 && n >= target {
+                return n;
+            }
+            if (vmin == 0 && vtime_ms == 0) || deadline.is_some_and(|d| now() >= d) {
+                return n;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Whether a read would return without waiting: a completed line in
+    /// canonical mode, any input byte in raw mode. Input that has arrived
+    /// is edited (and echoed) on the way, as a reader would.
+    ///
+    /// A port whose reader is already waiting reports no input; that
+    /// reader takes whatever arrives.
+    pub fn input_ready(&self) -> bool {
+        let termios = self.termios();
+        if termios.is_canonical() {
+            let Some(mut pending) = self.pending.try_lock() else {
+                return false;
+            };
+            return self.cook_line(&mut pending, &termios, false);
+        }
+        self.poll();
+        self.rx.try_lock().is_some_and(|rx| rx.len > 0)
+    }
+
+    /// Write output, applying `OPOST`/`ONLCR`.
+    pub fn write(&self, data: &[u8]) {
+        let termios = self.termios();
+        let onlcr = termios.c_oflag & (OPOST | ONLCR) == OPOST | ONLCR;
+        if self.is_console() {
+            console::write_user(self, data, onlcr);
+            return;
+        }
+        let _irq = crate::arch::disable_interrupts();
+        let mut uart = self.uart.lock();
+        for &byte in data {
+            if onlcr && byte == b'\n' {
+                uart.write_byte(b'\r');
+            }
+            uart.write_byte(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::terminal::{ECHO, ICANON};
+
+    /// A UART that never receives; tests put input in the ring directly.
+    struct IdleUart;
+
+    impl Uart for IdleUart {
+        fn init(&mut self) {}
+        fn configure(&mut self, _line: LineConfig) {}
+        fn try_read(&mut self) -> Option<u8> {
+            None
+        }
+        fn write_byte(&mut self, _byte: u8) {}
+        fn set_rx_interrupt(&mut self, _enabled: bool) {}
+    }
+
+    fn quiet_tty(canonical: bool) -> SerialTty {
+        let tty = SerialTty::new(String::from("ttyS1"), 1, Box::new(IdleUart));
+        let mut termios = KernelTermios::default_console();
+        termios.c_lflag &= !ECHO;
+        if !canonical {
+            termios.c_lflag &= !ICANON;
+        }
+        *tty.termios.lock() = termios;
+        tty
+    }
+
+    fn receive(tty: &SerialTty, input: &[u8]) {
+        let mut rx = tty.rx.lock();
+        for &byte in input {
+            assert!(rx.push(byte));
+        }
+    }
+
+    fn feed(line: &mut Vec<u8>, input: &[u8], termios: &KernelTermios) -> (bool, Vec<u8>) {
+        let mut echo = Vec::new();
+        let mut done = false;
+        for &byte in input {
+            if let Some(byte) = map_input(byte, termios) {
+                done = edit_line(line, byte, termios, &mut echo);
+            }
+        }
+        (done, echo)
+    }
+
+    #[test]
+    fn test_rx_ring_wraps_and_fills() {
+        let mut ring = RxRing::new();
+        for i in 0..RX_RING_SIZE {
+            assert!(ring.push(i as u8));
+        }
+        assert!(!ring.push(0));
+        assert_eq!(ring.pop(), Some(0));
+        assert!(ring.push(0xAA));
+        for i in 1..RX_RING_SIZE {
+            assert_eq!(ring.pop(), Some(i as u8));
+        }
+        assert_eq!(ring.pop(), Some(0xAA));
+        assert_eq!(ring.pop(), None);
+    }
+
+    #[test]
+    fn test_canonical_editing() {
+        let termios = KernelTermios::default_console();
+        let mut line = Vec::new();
+        let (done, echo) = feed(&mut line, b"rooz\x7ft\r", &termios);
+        assert!(done);
+        assert_eq!(line, b"root\n");
+        assert_eq!(echo, b"rooz\x08 \x08t\n");
+
+        // VKILL erases the whole line
+        let mut line = Vec::new();
+        let (done, _) = feed(&mut line, b"abc\x15", &termios);
+        assert!(!done);
+        assert!(line.is_empty());
+    }
+
+    #[test]
+    fn test_canonical_eof_and_no_echo() {
+        let mut termios = KernelTermios::default_console();
+        termios.c_lflag &= !ECHO;
+        let mut line = Vec::new();
+        let (done, echo) = feed(&mut line, b"\x04", &termios);
+        assert!(done);
+        assert!(line.is_empty());
+        assert!(echo.is_empty());
+        assert!(termios.c_lflag & ICANON != 0);
+    }
+
+    #[test]
+    fn test_input_mapping() {
+        let mut termios = KernelTermios::default_console();
+        assert_eq!(map_input(b'\r', &termios), Some(b'\n'));
+        termios.c_iflag = IGNCR;
+        assert_eq!(map_input(b'\r', &termios), None);
+        termios.c_iflag = INLCR;
+        assert_eq!(map_input(b'\n', &termios), Some(b'\r'));
+        termios.c_iflag = 0;
+        assert_eq!(map_input(b'\r', &termios), Some(b'\r'));
+    }
+
+    #[test]
+    fn test_canonical_input_ready_needs_a_line() {
+        let tty = quiet_tty(true);
+        assert!(!tty.input_ready());
+
+        receive(&tty, b"ls");
+        assert!(!tty.input_ready());
+        receive(&tty, b"\r");
+        assert!(tty.input_ready());
+
+        // The line cooked while polling is what the read returns
+        let mut buf = [0u8; 8];
+        assert_eq!(tty.read(&mut buf), 3);
+        assert_eq!(&buf[..3], b"ls\n");
+        assert!(!tty.input_ready());
+    }
+
+    #[test]
+    fn test_raw_input_ready_on_any_byte() {
+        let tty = quiet_tty(false);
+        assert!(!tty.input_ready());
+        receive(&tty, b"q");
+        assert!(tty.input_ready());
+    }
+}
@@ -8,7 +8,7 @@ use spin::Mutex;
 
 use crate::{
     error::KernelError,
-    services::driver_framework::{DeviceClass, DeviceInfo, DeviceStatus, Driver},
+    services::driver_framework::{DeviceClass, DeviceInfo, DeviceStatus, Driver, PowerState},
     sync::once_lock::OnceLock,
 };
 
@@ -474,7 +474,7 @@ impl Driver for AtaDriver {
         Ok(())
     }
 
-    fn suspend(&mut self) -> Result<(), KernelError> {
+    fn suspend(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         // Flush cache and put device in standby
         self.flush()?;
 
@@ -486,7 +486,7 @@ impl Driver for AtaDriver {
         Ok(())
     }
 
-    fn resume(&mut self) -> Result<(), KernelError> {
+    fn resume(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
         // Device should wake up automatically on next access
         self.wait_ready()?;
         crate::println!("[ATA] Device resumed");
@@ -670,6 +670,8 @@ pub fn init() {
         io_ports: vec![(0x1F0, 0x1F7), (0x3F6, 0x3F6)],
         memory_regions: Vec::new(),
         status: DeviceStatus::Uninitialized,
+        parent: None,
+        power: PowerState::On,
     };
 
     let ata_driver = AtaDriver::new(
@@ -31,12 +31,18 @@ pub const TIOCSWINSZ: usize = 0x5414;
 pub const TIOCGPGRP: usize = 0x540F;
 /// Set foreground process group ID.
 pub const TIOCSPGRP: usize = 0x5410;
+/// Make the terminal the controlling terminal of the caller's session.
+pub const TIOCSCTTY: usize = 0x540E;
+/// Give up the controlling terminal.
+pub const TIOCNOTTY: usize = 0x5422;
 
 // =========================================================================
 // termios flag constants (matching POSIX / Linux values)
 // =========================================================================
 
 // c_iflag bits
+pub const INLCR: u32 = 0o0000100;
+pub const IGNCR: u32 = 0o0000200;
 pub const ICRNL: u32 = 0o0000400;
 pub const IXON: u32 = 0o0002000;
 
@@ -45,8 +51,15 @@ pub const OPOST: u32 = 0o0000001;
 pub const ONLCR: u32 = 0o0000004;
 
 // c_cflag bits
+pub const CSIZE: u32 = 0o0000060;
+pub const CS5: u32 = 0o0000000;
+pub const CS6: u32 = 0o0000020;
+pub const CS7: u32 = 0o0000040;
 pub const CS8: u32 = 0o0000060;
+pub const CSTOPB: u32 = 0o0000100;
 pub const CREAD: u32 = 0o0000200;
+pub const PARENB: u32 = 0o0000400;
+pub const PARODD: u32 = 0o0001000;
 pub const HUPCL: u32 = 0o0002000;
 
 // c_lflag bits
@@ -226,7 +239,8 @@ impl TerminalState {
 /// Global terminal state for /dev/console.
 ///
 /// Protected by a spin::Mutex since it is accessed from syscall context.
-/// Future: per-device terminal state when multiple TTYs are supported.
+/// Serial ports other than the console keep their own state in
+/// [`super::serial::SerialTty`].
 static CONSOLE_TERMINAL: OnceLock<Mutex<TerminalState>> = OnceLock::new();
 
 /// Initialize the global console terminal state.
@@ -316,9 +330,13 @@ pub fn get_winsize_snapshot() -> KernelWinsize {
 }
 
 /// Set the terminal attributes (from TCSETS/TCSETSW/TCSETSF ioctl).
+///
+/// The console is `ttyS0`, so changed line settings (speed, character
+/// size, parity) are programmed into its UART.
 pub fn set_termios(new_termios: &KernelTermios) {
     if let Some(term) = get_console_terminal() {
-        term.lock().termios = *new_termios;
+        let old = core::mem::replace(&mut term.lock().termios, *new_termios);
+        super::serial::console_termios_changed(&old, new_termios);
     }
 }
 
@@ -11,7 +11,9 @@ use spin::RwLock;
 use super::{host::UsbHostController, transfer::UsbTransfer};
 use crate::{
     error::KernelError,
-    services::driver_framework::{Bus, DeviceClass, DeviceId, DeviceInfo, DeviceStatus},
+    services::driver_framework::{
+        Bus, DeviceClass, DeviceId, DeviceInfo, DeviceStatus, PowerState,
+    },
 };
 
 /// USB device speeds
@@ -586,6 +588,8 @@ impl Bus for UsbBus {
                 } else {
                     DeviceStatus::Removed
                 },
+                parent: None,
+                power: PowerState::On,
             };
 
             device_infos.push(device_info);
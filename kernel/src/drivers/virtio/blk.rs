@@ -15,6 +15,17 @@
 //! 3. **Status** (device-writable): single byte result (0 = OK, 1 = IOERR, 2 =
 //!    UNSUPP)
 //!
+//! A flush request has no data descriptor.
+//!
+//! # Write ordering
+//!
+//! Requests are polled to completion one at a time, so writes complete in
+//! the order they are issued; only the device's write cache can reorder
+//! them on the way to the disk. A device offering `VIRTIO_BLK_F_FLUSH` has
+//! such a cache: a write barrier is a flush request, and as virtio-blk has
+//! no Force Unit Access flag, a FUA write is a write followed by a flush.
+//! Without the feature the device writes through, and both are free.
+//!
 //! # QEMU usage
 //!
 //! ```text
@@ -67,7 +78,6 @@ mod req_type {
     /// Write sectors to the device
     pub const VIRTIO_BLK_T_OUT: u32 = 1;
     /// Flush volatile write cache
-    #[allow(dead_code)] // Virtio-blk command type per spec
     pub const VIRTIO_BLK_T_FLUSH: u32 = 4;
 }
 
@@ -226,7 +236,6 @@ pub struct VirtioBlkDevice {
     /// Whether the device is read-only (VIRTIO_BLK_F_RO)
     read_only: bool,
     /// Negotiated features
-    #[allow(dead_code)] // Negotiated feature bits for device capabilities
     features: u32,
 }
 
@@ -284,10 +293,15 @@ impl VirtioBlkDevice {
         let capacity_sectors = transport.read_device_config_u64(0);
 
         crate::println!(
-            "[VIRTIO-BLK] Initialized: {} sectors ({} KB), {}",
+            "[VIRTIO-BLK] Initialized: {} sectors ({} KB), {}, {}",
             capacity_sectors,
             capacity_sectors * BLOCK_SIZE as u64 / 1024,
-            if read_only { "read-only" } else { "read-write" }
+            if read_only { "read-only" } else { "read-write" },
+            if accepted & features::VIRTIO_BLK_F_FLUSH != 0 {
+                "write-back"
+            } else {
+                "write-through"
+            }
         );
 
         Ok(Self {
@@ -331,6 +345,11 @@ impl VirtioBlkDevice {
         self.read_only
     }
 
+    /// Whether the device has a volatile write cache (VIRTIO_BLK_F_FLUSH).
+    pub fn has_write_cache(&self) -> bool {
+        self.features & features::VIRTIO_BLK_F_FLUSH != 0
+    }
+
     /// Read a single block (512 bytes) from the device.
     ///
     /// `block_num` is the 0-based sector number. `buf` must be at least 512
@@ -378,10 +397,27 @@ impl VirtioBlkDevice {
         self.do_request(req_type::VIRTIO_BLK_T_OUT, block_num, None, Some(data))
     }
 
+    /// Write a single block (512 bytes) with Force Unit Access: on return it
+    /// is on stable storage.
+    pub fn write_block_fua(&mut self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        self.write_block(block_num, data)?;
+        self.flush()
+    }
+
+    /// Flush the device's write cache: every completed write is on stable
+    /// storage when this returns. A no-op on a write-through device.
+    pub fn flush(&mut self) -> Result<(), KernelError> {
+        if !self.has_write_cache() {
+            return Ok(());
+        }
+        self.do_request(req_type::VIRTIO_BLK_T_FLUSH, 0, None, None)
+    }
+
     /// Submit a block request and poll for completion.
     ///
     /// For IN (read): `read_buf` receives the data after completion.
     /// For OUT (write): `write_data` provides the data to write.
+    /// For FLUSH: neither; the request carries no data.
     fn do_request(
         &mut self,
         type_: u32,
@@ -389,7 +425,11 @@ impl VirtioBlkDevice {
         read_buf: Option<&mut [u8]>,
         write_data: Option<&[u8]>,
     ) -> Result<(), KernelError> {
-        let data_len = BLOCK_SIZE;
+        let data_len = if type_ == req_type::VIRTIO_BLK_T_FLUSH {
+            0
+        } else {
+            BLOCK_SIZE
+        };
 
         // Allocate DMA buffer for the request
         let req_buf = RequestBuffer::new(data_len)?;
@@ -402,7 +442,7 @@ impl VirtioBlkDevice {
             req_buf.write_data(&data[..data_len]);
         }
 
-        // Build a 3-descriptor chain:
+        // Build a 3-descriptor chain (2 for a flush, which has no data):
         //   [0] header (device-readable)
         //   [1] data   (device-writable for read, device-readable for write)
         //   [2] status (device-writable)
@@ -413,20 +453,26 @@ impl VirtioBlkDevice {
             .ok_or(KernelError::ResourceExhausted {
                 resource: "virtio-blk descriptors",
             })?;
-        let desc_data = match self.queue.alloc_desc() {
-            Some(d) => d,
-            None => {
-                self.queue.free_desc(desc_header);
-                return Err(KernelError::ResourceExhausted {
-                    resource: "virtio-blk descriptors",
-                });
+        let desc_data = if data_len > 0 {
+            match self.queue.alloc_desc() {
+                Some(d) => Some(d),
+                None => {
+                    self.queue.free_desc(desc_header);
+                    return Err(KernelError::ResourceExhausted {
+                        resource: "virtio-blk descriptors",
+                    });
+                }
             }
+        } else {
+            None
         };
         let desc_status = match self.queue.alloc_desc() {
             Some(d) => d,
             None => {
                 self.queue.free_desc(desc_header);
-                self.queue.free_desc(desc_data);
+                if let Some(desc_data) = desc_data {
+                    self.queue.free_desc(desc_data);
+                }
                 return Err(KernelError::ResourceExhausted {
                     resource: "virtio-blk descriptors",
                 });
@@ -442,26 +488,28 @@ impl VirtioBlkDevice {
                 req_buf.header_phys,
                 core::mem::size_of::<VirtioBlkReqHeader>() as u32,
                 VIRTQ_DESC_F_NEXT,
-                desc_data,
+                desc_data.unwrap_or(desc_status),
             );
         }
 
         // Descriptor 1: Data (direction depends on request type)
-        let data_flags = if type_ == req_type::VIRTIO_BLK_T_IN {
-            VIRTQ_DESC_F_WRITE | VIRTQ_DESC_F_NEXT // Device writes data
-        } else {
-            VIRTQ_DESC_F_NEXT // Device reads data (driver-written)
-        };
-        // SAFETY: desc_data is a valid allocated descriptor. data_phys points
-        // to valid DMA memory of at least data_len bytes.
-        unsafe {
-            self.queue.write_desc(
-                desc_data,
-                req_buf.data_phys,
-                data_len as u32,
-                data_flags,
-                desc_status,
-            );
+        if let Some(desc_data) = desc_data {
+            let data_flags = if type_ == req_type::VIRTIO_BLK_T_IN {
+                VIRTQ_DESC_F_WRITE | VIRTQ_DESC_F_NEXT // Device writes data
+            } else {
+                VIRTQ_DESC_F_NEXT // Device reads data (driver-written)
+            };
+            // SAFETY: desc_data is a valid allocated descriptor. data_phys
+            // points to valid DMA memory of at least data_len bytes.
+            unsafe {
+                self.queue.write_desc(
+                    desc_data,
+                    req_buf.data_phys,
+                    data_len as u32,
+                    data_flags,
+                    desc_status,
+                );
+            }
         }
 
         // Descriptor 2: Status (device-writable, end of chain)
@@ -559,6 +607,24 @@ pub trait BlockDevice: Send + Sync {
 
     /// Check if the device is read-only.
     fn is_read_only(&self) -> bool;
+
+    /// Flush any cached writes to stable storage.
+    fn flush(&mut self) -> Result<(), KernelError> {
+        Ok(())
+    }
+
+    /// Write a block with Force Unit Access: on return it is on stable
+    /// storage.
+    fn write_block_fua(&mut self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        self.write_block(block_num, data)?;
+        self.flush()
+    }
+
+    /// Write barrier: every write completed before it reaches stable
+    /// storage before any write issued after it.
+    fn barrier(&mut self) -> Result<(), KernelError> {
+        self.flush()
+    }
 }
 
 impl BlockDevice for VirtioBlkDevice {
@@ -577,6 +643,14 @@ impl BlockDevice for VirtioBlkDevice {
     fn is_read_only(&self) -> bool {
         self.read_only
     }
+
+    fn flush(&mut self) -> Result<(), KernelError> {
+        VirtioBlkDevice::flush(self)
+    }
+
+    fn write_block_fua(&mut self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        VirtioBlkDevice::write_block_fua(self, block_num, data)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -635,34 +709,12 @@ fn init_x86_64() {
             device.device_id,
         );
 
-        // Get BAR0 I/O port address
-        let io_base = match device.bars.first() {
-            Some(bar) => match bar.get_io_address() {
-                Some(addr) => addr as u16,
-                None => {
-                    // Legacy devices sometimes use I/O BARs,
-                    // but QEMU may present an I/O BAR at BAR0.
-                    crate::println!("[VIRTIO-BLK] BAR0 is not an I/O BAR, skipping device");
-                    continue;
-                }
-            },
-            None => {
-                crate::println!("[VIRTIO-BLK] No BAR0 found, skipping device");
+        match attach_pci(device) {
+            Ok(()) => crate::println!("[VIRTIO-BLK] Device initialized and registered"),
+            Err(_e) => {
+                crate::println!("[VIRTIO-BLK] Failed to initialize device: {:?}", _e);
                 continue;
             }
-        };
-
-        // Enable I/O space, memory space, and bus mastering
-        enable_bus_master(device);
-
-        match VirtioBlkDevice::new(io_base) {
-            Ok(dev) => {
-                let _ = VIRTIO_BLK.set(Mutex::new(dev));
-                crate::println!("[VIRTIO-BLK] Device initialized and registered");
-            }
-            Err(e) => {
-                crate::println!("[VIRTIO-BLK] Failed to initialize device: {:?}", e);
-            }
         }
 
         // We only support one virtio-blk device for now
@@ -672,35 +724,78 @@ fn init_x86_64() {
     crate::println!("[VIRTIO-BLK] No virtio-blk devices found on PCI bus");
 }
 
+/// Drive the virtio-blk PCI function `device`.
+///
+/// Fails if the device has no I/O BAR0, does not initialize, or another
+/// virtio-blk device is already in use.
+#[cfg(target_arch = "x86_64")]
+pub fn attach_pci(device: &crate::drivers::pci::PciDevice) -> Result<(), KernelError> {
+    if is_initialized() {
+        return Err(KernelError::AlreadyExists {
+            resource: "virtio-blk device",
+            id: 0,
+        });
+    }
+
+    // Get BAR0 I/O port address. Legacy devices use an I/O BAR there, and so
+    // does QEMU's transitional virtio-blk.
+    let io_base = device
+        .bars
+        .first()
+        .and_then(|bar| bar.get_io_address())
+        .ok_or(KernelError::HardwareError {
+            device: "virtio-blk",
+            code: 0xdead0010,
+        })? as u16;
+
+    // Enable I/O space, memory space, and bus mastering
+    enable_bus_master(device);
+
+    let dev = VirtioBlkDevice::new(io_base)?;
+    let _ = VIRTIO_BLK.set(Mutex::new(dev));
+    Ok(())
+}
+
 /// AArch64 / RISC-V virtio-mmio initialization.
 ///
-/// Probes the architecture-specific MMIO base addresses for a virtio-blk
-/// device. On AArch64, these are at 0x0A00_0000 with 0x200 stride; on
-/// RISC-V, at 0x1000_1000 with 0x1000 stride. See
-/// [`super::mmio::DEFAULT_BASES`].
+/// Probes the virtio-mmio regions from the device tree for a virtio-blk
+/// device, falling back to the QEMU `virt` addresses. See
+/// [`super::mmio::device_bases`].
 #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
 fn init_mmio() {
-    use crate::drivers::virtio::mmio::{try_init_mmio_blk, DEFAULT_BASES};
-
-    // Probe the standard virtio-mmio base addresses exposed by QEMU virt.
-    for base in DEFAULT_BASES {
-        match try_init_mmio_blk(base) {
-            Ok(dev) => {
-                if VIRTIO_BLK.set(Mutex::new(dev)).is_ok() {
-                    crate::println!("[VIRTIO-BLK/MMIO] Device initialized at base {:#x}", base);
-                    return;
-                }
-            }
-            Err(_) => continue,
+    for base in super::mmio::device_bases() {
+        if attach_mmio(base).is_ok() {
+            crate::println!("[VIRTIO-BLK/MMIO] Device initialized at base {:#x}", base);
+            return;
         }
     }
 
     crate::println!("[VIRTIO-BLK/MMIO] No virtio-blk mmio device detected");
 }
 
+/// Drive the virtio-mmio block device at `base`.
+///
+/// Fails if there is no virtio-blk device at `base`, it does not
+/// initialize, or another virtio-blk device is already in use.
+pub fn attach_mmio(base: usize) -> Result<(), KernelError> {
+    if is_initialized() {
+        return Err(KernelError::AlreadyExists {
+            resource: "virtio-blk device",
+            id: 0,
+        });
+    }
+    let dev = super::mmio::try_init_mmio_blk(base)?;
+    VIRTIO_BLK
+        .set(Mutex::new(dev))
+        .map_err(|_| KernelError::AlreadyExists {
+            resource: "virtio-blk device",
+            id: 0,
+        })
+}
+
 /// Enable PCI I/O space, memory space, and bus mastering for a device.
 #[cfg(target_arch = "x86_64")]
-fn enable_bus_master(device: &crate::drivers::pci::PciDevice) {
+pub(super) fn enable_bus_master(device: &crate::drivers::pci::PciDevice) {
     let loc = device.location;
     let config_addr = loc.to_config_address() | (0x04 & 0xFC); // Command register at offset 0x04
 
@@ -729,7 +824,7 @@ pub fn is_initialized() -> bool {
 }
 
 /// Convert a physical address to a kernel-accessible virtual address.
-fn phys_to_kernel_virt(phys: u64) -> usize {
+pub(super) fn phys_to_kernel_virt(phys: u64) -> usize {
     #[cfg(target_arch = "x86_64")]
     {
         if let Some(virt) = crate::arch::x86_64::msr::phys_to_virt(phys as usize) {
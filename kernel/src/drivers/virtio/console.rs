@@ -0,0 +1,716 @@
+//! Virtio-console (serial port) device driver
+//!
+//! Implements the virtio console device described in the virtio
+//! specification, section 5.3. Without the MULTIPORT feature the device has
+//! a single port on queues 0 (receive) and 1 (transmit). With it, queues 2
+//! and 3 carry control messages and port N > 0 uses queues 2N+2 and 2N+3;
+//! the host announces its ports with control messages once the driver
+//! reports ready.
+//!
+//! Each port appears as `/dev/vport<D>p<N>` for console device `D`, giving
+//! the guest serial channels to the host beyond the UARTs (a guest agent, a
+//! log stream, a second shell). Reads never block: they return whatever the
+//! host has sent so far. The device is serviced whenever one of its ports
+//! is read or written, so a port the host adds later gets its node on the
+//! next access to the device.
+//!
+//! # QEMU usage
+//!
+//! ```text
+//! -device virtio-serial-pci -chardev socket,path=/tmp/agent,server=on,wait=off,id=agent
+//!     -device virtserialport,chardev=agent,name=org.veridian.agent
+//! ```
+
+use alloc::{collections::VecDeque, format, string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{self, Ordering};
+
+use spin::{Mutex, RwLock};
+
+use super::{
+    blk::phys_to_kernel_virt,
+    queue::{VirtQueue, VIRTQ_DESC_F_WRITE},
+    VirtioPciTransport, VirtioTransport,
+};
+use crate::{
+    error::KernelError,
+    mm::{FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
+};
+
+/// Virtio device type for consoles (virtio spec 5.3)
+const VIRTIO_ID_CONSOLE: u32 = 3;
+
+/// Feature bit: the device has several ports and a control queue pair
+const VIRTIO_CONSOLE_F_MULTIPORT: u32 = 1 << 1;
+
+/// Feature bit 32 (VIRTIO_F_VERSION_1), accepted from version 2 MMIO
+/// devices
+const VIRTIO_F_VERSION_1_HIGH: u32 = 1 << 0;
+
+/// Offset of `max_nr_ports` in the device configuration space
+const CONFIG_MAX_NR_PORTS: u16 = 4;
+
+/// Ports set up per device; the host's further ports are refused
+const MAX_PORTS: usize = 8;
+
+/// Receive buffers posted per receive queue, sharing one DMA frame
+const RX_BUFFERS: usize = 8;
+
+/// Size of each receive buffer
+const RX_BUFFER_SIZE: usize = FRAME_SIZE / RX_BUFFERS;
+
+/// Received bytes held per port before the oldest are dropped
+const INPUT_LIMIT: usize = 16 * 1024;
+
+/// Character device major number of `/dev/vport*`
+pub const VPORT_MAJOR: u32 = 243;
+
+/// Control message events (virtio spec 5.3.6.2)
+mod event {
+    pub const DEVICE_READY: u16 = 0;
+    pub const DEVICE_ADD: u16 = 1;
+    pub const DEVICE_REMOVE: u16 = 2;
+    pub const PORT_READY: u16 = 3;
+    pub const CONSOLE_PORT: u16 = 4;
+    pub const PORT_OPEN: u16 = 6;
+    pub const PORT_NAME: u16 = 7;
+}
+
+/// A control message, `struct virtio_console_control`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ControlMsg {
+    /// Port number
+    id: u32,
+    /// One of the [`event`] constants
+    event: u16,
+    value: u16,
+}
+
+impl ControlMsg {
+    const SIZE: usize = 8;
+
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::SIZE {
+            return None;
+        }
+        Some(Self {
+            id: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            event: u16::from_le_bytes([bytes[4], bytes[5]]),
+            value: u16::from_le_bytes([bytes[6], bytes[7]]),
+        })
+    }
+
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.id.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.event.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.value.to_le_bytes());
+        bytes
+    }
+}
+
+/// Receive and transmit queue indices of `port`.
+fn port_queues(port: usize) -> (u16, u16) {
+    let rx = if port == 0 { 0 } else { 2 * port as u16 + 2 };
+    (rx, rx + 1)
+}
+
+/// Name of the device node of `port` on console device `device`.
+fn node_name(device: usize, port: usize) -> String {
+    format!("vport{}p{}", device, port)
+}
+
+/// Console device and port of a `/dev/vport<D>p<N>` node name.
+fn parse_node_name(name: &str) -> Option<(usize, usize)> {
+    let (device, port) = name.strip_prefix("vport")?.split_once('p')?;
+    Some((device.parse().ok()?, port.parse().ok()?))
+}
+
+/// Set up virtqueue `index` of the device behind `transport`.
+fn setup_queue(transport: &VirtioTransport, index: u16) -> Result<VirtQueue, KernelError> {
+    transport.select_queue(index);
+    let size = transport.read_queue_size();
+    if size == 0 {
+        return Err(KernelError::HardwareError {
+            device: "virtio-console",
+            code: 0x01, // Queue size is zero -- no queue available
+        });
+    }
+    let queue = VirtQueue::new(size)?;
+    if let VirtioTransport::Mmio(m) = transport {
+        m.set_queue_size(queue.size());
+    }
+    transport.write_queue_address(queue.pfn());
+    transport.write_queue_phys(queue.phys_desc(), queue.phys_avail(), queue.phys_used());
+    transport.set_queue_ready();
+    Ok(queue)
+}
+
+/// One DMA frame, freed on drop.
+struct DmaFrame {
+    frame: FrameNumber,
+    phys: u64,
+    virt: usize,
+}
+
+impl DmaFrame {
+    fn new() -> Result<Self, KernelError> {
+        let frame = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(1, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: FRAME_SIZE,
+                available: 0,
+            })?;
+        let phys = frame.as_u64() * FRAME_SIZE as u64;
+        Ok(Self {
+            frame,
+            phys,
+            virt: phys_to_kernel_virt(phys),
+        })
+    }
+}
+
+impl Drop for DmaFrame {
+    fn drop(&mut self) {
+        let _ = FRAME_ALLOCATOR.lock().free_frames(self.frame, 1);
+    }
+}
+
+/// A receive queue, kept full of device-writable buffers.
+struct RxQueue {
+    index: u16,
+    queue: VirtQueue,
+    buffers: DmaFrame,
+    /// Descriptor of each buffer
+    descs: [u16; RX_BUFFERS],
+}
+
+impl RxQueue {
+    fn new(transport: &VirtioTransport, index: u16) -> Result<Self, KernelError> {
+        Ok(Self {
+            index,
+            queue: setup_queue(transport, index)?,
+            buffers: DmaFrame::new()?,
+            descs: [0; RX_BUFFERS],
+        })
+    }
+
+    /// Post every buffer to the device.
+    fn fill(&mut self, transport: &VirtioTransport) -> Result<(), KernelError> {
+        for i in 0..RX_BUFFERS {
+            let desc = self
+                .queue
+                .alloc_desc()
+                .ok_or(KernelError::ResourceExhausted {
+                    resource: "virtio-console descriptors",
+                })?;
+            // SAFETY: desc is a valid allocated descriptor index. Buffer i
+            // lies within our DMA frame, which lives as long as the queue.
+            unsafe {
+                self.queue.write_desc(
+                    desc,
+                    self.buffers.phys + (i * RX_BUFFER_SIZE) as u64,
+                    RX_BUFFER_SIZE as u32,
+                    VIRTQ_DESC_F_WRITE,
+                    0,
+                );
+            }
+            self.descs[i] = desc;
+            atomic::fence(Ordering::Release);
+            self.queue.push_avail(desc);
+        }
+        transport.notify_queue(self.index);
+        Ok(())
+    }
+
+    /// Pass each filled buffer to `f` and post it again.
+    fn drain(&mut self, transport: &VirtioTransport, mut f: impl FnMut(&[u8])) {
+        let mut reposted = false;
+        while let Some((desc, len)) = self.queue.poll_used() {
+            let Some(i) = self.descs.iter().position(|&d| d == desc) else {
+                continue;
+            };
+            let len = (len as usize).min(RX_BUFFER_SIZE);
+            // SAFETY: buffer i lies within our DMA frame and the device has
+            // returned it via the used ring, so it is done writing `len`
+            // bytes to it.
+            let data = unsafe {
+                core::slice::from_raw_parts(
+                    (self.buffers.virt + i * RX_BUFFER_SIZE) as *const u8,
+                    len,
+                )
+            };
+            f(data);
+            atomic::fence(Ordering::Release);
+            self.queue.push_avail(desc);
+            reposted = true;
+        }
+        if reposted {
+            transport.notify_queue(self.index);
+        }
+    }
+}
+
+/// A transmit queue, sending one buffer at a time.
+struct TxQueue {
+    index: u16,
+    queue: VirtQueue,
+    buffer: DmaFrame,
+}
+
+impl TxQueue {
+    fn new(transport: &VirtioTransport, index: u16) -> Result<Self, KernelError> {
+        Ok(Self {
+            index,
+            queue: setup_queue(transport, index)?,
+            buffer: DmaFrame::new()?,
+        })
+    }
+
+    /// Send (a prefix of) `data` and wait for the device to consume it.
+    /// Returns the number of bytes sent.
+    fn send(&mut self, transport: &VirtioTransport, data: &[u8]) -> Result<usize, KernelError> {
+        let len = data.len().min(FRAME_SIZE);
+        if len == 0 {
+            return Ok(0);
+        }
+        let desc = self
+            .queue
+            .alloc_desc()
+            .ok_or(KernelError::ResourceExhausted {
+                resource: "virtio-console descriptors",
+            })?;
+        // SAFETY: buffer.virt maps our DMA frame of FRAME_SIZE bytes and
+        // len <= FRAME_SIZE. The device owns no buffer in it: every send
+        // waits for the previous one to complete.
+        unsafe {
+            core::ptr::copy_nonoverlapping(data.as_ptr(), self.buffer.virt as *mut u8, len);
+            self.queue
+                .write_desc(desc, self.buffer.phys, len as u32, 0, 0);
+        }
+
+        atomic::fence(Ordering::Release);
+        self.queue.push_avail(desc);
+        transport.notify_queue(self.index);
+
+        let mut spins: u32 = 0;
+        const MAX_SPINS: u32 = 10_000_000;
+        while !self.queue.has_used() {
+            core::hint::spin_loop();
+            spins += 1;
+            if spins >= MAX_SPINS {
+                self.queue.free_chain(desc);
+                return Err(KernelError::Timeout {
+                    operation: "virtio-console transmit",
+                    duration_ms: 0,
+                });
+            }
+        }
+        let _ = self.queue.poll_used();
+        self.queue.free_chain(desc);
+        Ok(len)
+    }
+}
+
+/// A port of a console device.
+struct Port {
+    rx: RxQueue,
+    tx: TxQueue,
+    /// The host announced the port (always true without MULTIPORT)
+    added: bool,
+    /// Name given by the host, such as `org.qemu.guest_agent.0`
+    name: Option<String>,
+    /// The host side of the port is open
+    host_connected: bool,
+    /// Bytes received and not yet read
+    input: VecDeque<u8>,
+}
+
+/// What [`VirtioConsoleDevice::poll`] changed about the ports.
+#[derive(Debug, Default)]
+struct PortChanges {
+    added: Vec<usize>,
+    removed: Vec<usize>,
+}
+
+/// Virtio console device.
+pub struct VirtioConsoleDevice {
+    /// Transport handle (PCI or MMIO)
+    transport: VirtioTransport,
+    /// Control queue pair, present with MULTIPORT
+    control: Option<(RxQueue, TxQueue)>,
+    /// Ports, indexed by port number
+    ports: Vec<Port>,
+}
+
+impl VirtioConsoleDevice {
+    /// Probe and initialize a virtio-console device at the given PCI BAR0
+    /// I/O base.
+    pub fn new(io_base: u16) -> Result<Self, KernelError> {
+        Self::setup(VirtioTransport::Pci(VirtioPciTransport::new(io_base)))
+    }
+
+    /// Probe and initialize a virtio-console device behind an MMIO
+    /// transport (used on AArch64/RISC-V).
+    pub fn from_mmio(
+        transport: crate::drivers::virtio::mmio::VirtioMmioTransport,
+    ) -> Result<Self, KernelError> {
+        if !transport.matches_device(VIRTIO_ID_CONSOLE) {
+            return Err(KernelError::HardwareError {
+                device: "virtio-console-mmio",
+                code: 0xdead0001,
+            });
+        }
+        Self::setup(VirtioTransport::Mmio(transport))
+    }
+
+    /// Negotiate MULTIPORT, set up the queues of the control channel and of
+    /// up to [`MAX_PORTS`] ports, and tell the device the driver is ready.
+    fn setup(transport: VirtioTransport) -> Result<Self, KernelError> {
+        transport.begin_init();
+
+        let accepted = transport.read_device_features() & VIRTIO_CONSOLE_F_MULTIPORT;
+        transport.write_guest_features(accepted);
+        if let VirtioTransport::Mmio(m) = &transport {
+            if m.version() >= 2 {
+                let _ = m.read_device_features_high();
+                m.write_driver_features_high(VIRTIO_F_VERSION_1_HIGH);
+            }
+        }
+        let features_ok = transport.set_features_ok();
+        if !features_ok && matches!(transport, VirtioTransport::Mmio(_)) {
+            // Legacy PCI devices may ignore FEATURES_OK; MMIO must accept it
+            return Err(KernelError::HardwareError {
+                device: "virtio-console",
+                code: 0x04,
+            });
+        }
+
+        let multiport = accepted != 0;
+        let nr_ports = if multiport {
+            (transport.read_device_config_u32(CONFIG_MAX_NR_PORTS) as usize).clamp(1, MAX_PORTS)
+        } else {
+            1
+        };
+
+        let mut ports = Vec::with_capacity(nr_ports);
+        let mut control = None;
+        for port in 0..nr_ports {
+            let (rx, tx) = port_queues(port);
+            ports.push(Port {
+                rx: RxQueue::new(&transport, rx)?,
+                tx: TxQueue::new(&transport, tx)?,
+                added: !multiport,
+                name: None,
+                host_connected: !multiport,
+                input: VecDeque::new(),
+            });
+            if port == 0 && multiport {
+                control = Some((RxQueue::new(&transport, 2)?, TxQueue::new(&transport, 3)?));
+            }
+        }
+
+        transport.set_driver_ok();
+
+        let mut device = Self {
+            transport,
+            control,
+            ports,
+        };
+        for port in &mut device.ports {
+            port.rx.fill(&device.transport)?;
+        }
+        if let Some((rx, _)) = &mut device.control {
+            rx.fill(&device.transport)?;
+        }
+        device.send_control(0, event::DEVICE_READY, 1)?;
+        Ok(device)
+    }
+
+    /// Send a control message to the device.
+    fn send_control(&mut self, id: u32, event: u16, value: u16) -> Result<(), KernelError> {
+        let Some((_, tx)) = &mut self.control else {
+            return Ok(());
+        };
+        let msg = ControlMsg { id, event, value }.to_bytes();
+        tx.send(&self.transport, &msg).map(|_| ())
+    }
+
+    /// Process control messages and move received data into the port
+    /// input buffers.
+    fn poll(&mut self) -> PortChanges {
+        let mut messages = Vec::new();
+        if let Some((rx, _)) = &mut self.control {
+            rx.drain(&self.transport, |data| {
+                if let Some(msg) = ControlMsg::parse(data) {
+                    messages.push((msg, Vec::from(&data[ControlMsg::SIZE..])));
+                }
+            });
+        }
+        let mut changes = PortChanges::default();
+        for (msg, payload) in messages {
+            self.handle_control(msg, &payload, &mut changes);
+        }
+
+        for port in &mut self.ports {
+            let input = &mut port.input;
+            port.rx.drain(&self.transport, |data| {
+                input.extend(data);
+                let excess = input.len().saturating_sub(INPUT_LIMIT);
+                input.drain(..excess);
+            });
+        }
+        changes
+    }
+
+    fn handle_control(&mut self, msg: ControlMsg, payload: &[u8], changes: &mut PortChanges) {
+        let index = msg.id as usize;
+        if msg.event == event::DEVICE_ADD && index >= self.ports.len() {
+            // No queues set up for it: refuse the port
+            let _ = self.send_control(msg.id, event::PORT_READY, 0);
+            return;
+        }
+        let Some(port) = self.ports.get_mut(index) else {
+            return;
+        };
+        match msg.event {
+            event::DEVICE_ADD => {
+                if !port.added {
+                    port.added = true;
+                    changes.added.push(index);
+                }
+                let _ = self.send_control(msg.id, event::PORT_READY, 1);
+                // The guest side is open for as long as the driver runs.
+                let _ = self.send_control(msg.id, event::PORT_OPEN, 1);
+            }
+            event::DEVICE_REMOVE => {
+                if port.added {
+                    port.added = false;
+                    port.name = None;
+                    port.host_connected = false;
+                    port.input.clear();
+                    changes.removed.push(index);
+                }
+            }
+            event::PORT_NAME => {
+                let name = payload.split(|&b| b == 0).next().unwrap_or_default();
+                port.name = core::str::from_utf8(name).ok().map(String::from);
+            }
+            event::PORT_OPEN => port.host_connected = msg.value != 0,
+            event::CONSOLE_PORT => {
+                let _ = self.send_control(msg.id, event::PORT_OPEN, 1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Read up to `buf.len()` bytes received on `port`.
+    fn read(&mut self, port: usize, buf: &mut [u8]) -> usize {
+        let Some(port) = self.ports.get_mut(port).filter(|p| p.added) else {
+            return 0;
+        };
+        let len = buf.len().min(port.input.len());
+        for (dst, src) in buf.iter_mut().zip(port.input.drain(..len)) {
+            *dst = src;
+        }
+        len
+    }
+
+    /// Send all of `data` on `port`.
+    fn write(&mut self, port: usize, data: &[u8]) -> Result<usize, KernelError> {
+        let Some(port) = self.ports.get_mut(port).filter(|p| p.added) else {
+            return Err(KernelError::NotFound {
+                resource: "virtio-console port",
+                id: port as u64,
+            });
+        };
+        let mut sent = 0;
+        while sent < data.len() {
+            sent += port.tx.send(&self.transport, &data[sent..])?;
+        }
+        Ok(sent)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global driver instances
+// ---------------------------------------------------------------------------
+
+/// Initialized console devices, indexed by the `D` of `/dev/vport<D>p<N>`.
+static DEVICES: RwLock<Vec<Arc<Mutex<VirtioConsoleDevice>>>> = RwLock::new(Vec::new());
+
+/// A port of a console device, as reported by [`ports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortInfo {
+    /// Device node name, `vport<D>p<N>`
+    pub node: String,
+    /// Name given by the host
+    pub name: Option<String>,
+    /// The host side of the port is open
+    pub host_connected: bool,
+}
+
+/// Service console device `index`, creating and removing the device nodes
+/// of ports the host added or removed.
+fn service(index: usize) -> Option<Arc<Mutex<VirtioConsoleDevice>>> {
+    let device = DEVICES.read().get(index).cloned()?;
+    let changes = device.lock().poll();
+    for port in changes.added {
+        crate::fs::devfs::register_char_device(
+            &node_name(index, port),
+            VPORT_MAJOR,
+            (index * MAX_PORTS + port) as u32,
+        );
+    }
+    for port in changes.removed {
+        crate::fs::devfs::unregister_device(&node_name(index, port));
+    }
+    Some(device)
+}
+
+/// Add an initialized device and create the nodes of its ports.
+fn register(device: VirtioConsoleDevice) -> usize {
+    let index = {
+        let mut devices = DEVICES.write();
+        devices.push(Arc::new(Mutex::new(device)));
+        devices.len() - 1
+    };
+    // Without MULTIPORT, port 0 needs no announcement.
+    if let Some(device) = service(index) {
+        let device = device.lock();
+        if device.control.is_none() {
+            crate::fs::devfs::register_char_device(
+                &node_name(index, 0),
+                VPORT_MAJOR,
+                (index * MAX_PORTS) as u32,
+            );
+        }
+    }
+    index
+}
+
+/// Attach the driver to a virtio-console PCI function.
+///
+/// Fails if the device has no I/O BAR0 or does not initialize.
+#[cfg(target_arch = "x86_64")]
+pub fn attach_pci(device: &crate::drivers::pci::PciDevice) -> Result<(), KernelError> {
+    let io_base = device
+        .bars
+        .first()
+        .and_then(|bar| bar.get_io_address())
+        .ok_or(KernelError::HardwareError {
+            device: "virtio-console",
+            code: 0xdead0010,
+        })? as u16;
+
+    super::blk::enable_bus_master(device);
+
+    let _index = register(VirtioConsoleDevice::new(io_base)?);
+    crate::println!(
+        "[VIRTIO-CONSOLE] vport{} at {}:{}:{}",
+        _index,
+        device.location.bus,
+        device.location.device,
+        device.location.function,
+    );
+    Ok(())
+}
+
+/// Attach the driver to the virtio-mmio device at `base`.
+///
+/// Fails if there is no virtio-console device at `base` or it does not
+/// initialize.
+pub fn attach_mmio(base: usize) -> Result<(), KernelError> {
+    let device = VirtioConsoleDevice::from_mmio(super::mmio::VirtioMmioTransport::new(base))?;
+    let _index = register(device);
+    crate::println!("[VIRTIO-CONSOLE/MMIO] vport{} at base {:#x}", _index, base);
+    Ok(())
+}
+
+/// Whether `name` is the name of a virtio-console port node.
+pub fn is_port_node(name: &str) -> bool {
+    parse_node_name(name).is_some()
+}
+
+/// Read up to `buf.len()` bytes received on the port behind
+/// `/dev/<node>`. Returns 0 when nothing is waiting.
+pub fn read(node: &str, buf: &mut [u8]) -> Result<usize, KernelError> {
+    let (index, port) = parse_node_name(node).ok_or(KernelError::NotFound {
+        resource: "virtio-console port",
+        id: 0,
+    })?;
+    let device = service(index).ok_or(KernelError::NotFound {
+        resource: "virtio-console device",
+        id: index as u64,
+    })?;
+    let read = device.lock().read(port, buf);
+    Ok(read)
+}
+
+/// Send `data` on the port behind `/dev/<node>`.
+pub fn write(node: &str, data: &[u8]) -> Result<usize, KernelError> {
+    let (index, port) = parse_node_name(node).ok_or(KernelError::NotFound {
+        resource: "virtio-console port",
+        id: 0,
+    })?;
+    let device = service(index).ok_or(KernelError::NotFound {
+        resource: "virtio-console device",
+        id: index as u64,
+    })?;
+    let written = device.lock().write(port, data);
+    written
+}
+
+/// The ports of every console device.
+pub fn ports() -> Vec<PortInfo> {
+    let count = DEVICES.read().len();
+    let mut ports = Vec::new();
+    for index in 0..count {
+        let Some(device) = service(index) else {
+            continue;
+        };
+        let device = device.lock();
+        for (port, state) in device.ports.iter().enumerate().filter(|(_, p)| p.added) {
+            ports.push(PortInfo {
+                node: node_name(index, port),
+                name: state.name.clone(),
+                host_connected: state.host_connected,
+            });
+        }
+    }
+    ports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_queues() {
+        assert_eq!(port_queues(0), (0, 1));
+        assert_eq!(port_queues(1), (4, 5));
+        assert_eq!(port_queues(3), (8, 9));
+    }
+
+    #[test]
+    fn test_node_name_round_trip() {
+        assert_eq!(node_name(1, 3), "vport1p3");
+        assert_eq!(parse_node_name("vport1p3"), Some((1, 3)));
+        assert_eq!(parse_node_name("vport0"), None);
+        assert_eq!(parse_node_name("vportxp1"), None);
+        assert_eq!(parse_node_name("ttyS0"), None);
+    }
+
+    #[test]
+    fn test_control_msg_round_trip() {
+        let msg = ControlMsg {
+            id: 2,
+            event: event::PORT_OPEN,
+            value: 1,
+        };
+        let bytes = msg.to_bytes();
+        assert_eq!(bytes, [2, 0, 0, 0, 6, 0, 1, 0]);
+        assert_eq!(ControlMsg::parse(&bytes), Some(msg));
+        assert_eq!(ControlMsg::parse(&bytes[..7]), None);
+    }
+}
@@ -7,12 +7,13 @@
 //!
 //! # Default MMIO Base Addresses
 //!
-//! The `DEFAULT_BASES` array lists the first four virtio-mmio device regions
-//! for QEMU's `virt` machine. Each region is 0x200 bytes (512 bytes) and
-//! contains the standard virtio-mmio register set at the offsets defined in
-//! the `regs` module. Valid register offsets range from 0x000 to 0x0A4.
-//! Device-specific configuration space starts at offset 0x100 (modern) or
-//! STATUS + 0x14 (legacy).
+//! Device regions come from the `virtio,mmio` nodes of the device tree (see
+//! [`device_bases`]). Without one, the `DEFAULT_BASES` array lists the first
+//! four virtio-mmio device regions for QEMU's `virt` machine. Each region is
+//! 0x200 bytes (512 bytes) and contains the standard virtio-mmio register set
+//! at the offsets defined in the `regs` module. Valid register offsets range
+//! from 0x000 to 0x0A4. Device-specific configuration space starts at offset
+//! 0x100 (modern) or STATUS + 0x14 (legacy).
 //!
 //! # Usage
 //!
@@ -22,6 +23,7 @@
 
 // Virtio MMIO transport -- AArch64/RISC-V device access
 
+use alloc::vec::Vec;
 use core::ptr;
 
 use crate::{
@@ -50,6 +52,17 @@ pub const DEFAULT_BASES: [usize; 4] = [0x1000_1000, 0x1000_2000, 0x1000_3000, 0x
 #[cfg(not(any(target_arch = "aarch64", target_arch = "riscv64")))]
 pub const DEFAULT_BASES: [usize; 4] = [0x0a00_0000, 0x0a00_2000, 0x0a00_4000, 0x0a00_6000];
 
+/// Virtio-mmio regions to probe: the `virtio,mmio` nodes of the device tree,
+/// or [`DEFAULT_BASES`] when the firmware passed none.
+pub fn device_bases() -> Vec<usize> {
+    let bases = crate::drivers::dt::mmio_bases("virtio,mmio");
+    if bases.is_empty() {
+        DEFAULT_BASES.to_vec()
+    } else {
+        bases
+    }
+}
+
 /// MMIO register offsets (per virtio spec 4.2.2, legacy interface).
 ///
 /// Valid offsets range from 0x000 (MAGIC) to 0x0A4 (QUEUE_USED_HIGH).
@@ -81,6 +94,8 @@ mod regs {
     pub const QUEUE_AVAIL_HIGH: usize = 0x094;
     pub const QUEUE_USED_LOW: usize = 0x0a0;
     pub const QUEUE_USED_HIGH: usize = 0x0a4;
+    pub const CONFIG: usize = 0x100; // Device-specific configuration (version
+                                     // 2)
 }
 
 /// Virtio-mmio status flags (same as PCI transport)
@@ -135,8 +150,27 @@ impl VirtioMmioTransport {
     }
 
     pub fn matches_blk(&self) -> bool {
+        self.matches_device(2) // 2 = block device
+    }
+
+    /// Whether a virtio device with the given device type lives here.
+    pub fn matches_device(&self, device_id: u32) -> bool {
         self.read32(regs::MAGIC) == 0x7472_6976 // "virt"
-            && self.read32(regs::DEVICE_ID) == 2 // 2 = block device
+            && self.read32(regs::DEVICE_ID) == device_id
+    }
+
+    /// Virtio device type of the device here, or `None` for a region
+    /// without a device (QEMU leaves unused slots with device type 0).
+    pub fn device_type(&self) -> Option<u32> {
+        if self.read32(regs::MAGIC) != 0x7472_6976 {
+            return None;
+        }
+        Some(self.read32(regs::DEVICE_ID)).filter(|&id| id != 0)
+    }
+
+    /// Reset the device, which stops it using its virtqueues.
+    pub fn reset(&self) {
+        self.write32(regs::STATUS, 0);
     }
 
     pub fn begin_init(&self) {
@@ -172,6 +206,19 @@ impl VirtioMmioTransport {
         self.write32(regs::DRIVER_FEATURES, features);
     }
 
+    /// Feature bits 32..63 offered by the device.
+    pub fn read_device_features_high(&self) -> u32 {
+        self.write32(regs::DEVICE_FEATURES_SEL, 1);
+        self.read32(regs::DEVICE_FEATURES)
+    }
+
+    /// Accept feature bits 32..63. Modern-only devices (virtio-snd) refuse
+    /// FEATURES_OK unless bit 32, VIRTIO_F_VERSION_1, is accepted.
+    pub fn write_driver_features_high(&self, features: u32) {
+        self.write32(regs::DRIVER_FEATURES_SEL, 1);
+        self.write32(regs::DRIVER_FEATURES, features);
+    }
+
     pub fn select_queue(&self, idx: u16) {
         self.write32(regs::QUEUE_SEL, idx as u32);
     }
@@ -216,6 +263,19 @@ impl VirtioMmioTransport {
         (hi << 32) | lo
     }
 
+    /// Read a 32-bit field of the device-specific configuration space of a
+    /// version 2 device.
+    pub fn read_config_u32(&self, offset: usize) -> u32 {
+        self.read32(regs::CONFIG + offset)
+    }
+
+    /// Read a byte of the device-specific configuration space.
+    pub fn read_config_u8(&self, offset: usize) -> u8 {
+        // SAFETY: base + CONFIG + offset is in the device's MMIO region,
+        // mapped in the kernel's phys window.
+        unsafe { ptr::read_volatile((self.base + regs::CONFIG + offset) as *const u8) }
+    }
+
     pub fn version(&self) -> u32 {
         self.read32(regs::VERSION)
     }
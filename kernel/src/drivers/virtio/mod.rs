@@ -24,6 +24,9 @@
 //!     |
 //!     +-- VirtQueue (queue.rs)         -- split virtqueue (shared)
 //!     +-- VirtioBlkDevice (blk.rs)     -- block device driver (shared)
+//!     +-- VirtioRngDevice (rng.rs)     -- entropy source driver (shared)
+//!     +-- VirtioConsoleDevice (console.rs) -- serial ports (shared)
+//!     +-- Virtio9pDevice (p9.rs)       -- 9P file sharing transport (shared)
 //! ```
 //!
 //! # Legacy PCI Layout (BAR0 I/O Space)
@@ -48,8 +51,11 @@
 // Virtio transport layer -- PCI and MMIO backends for device drivers
 
 pub mod blk;
+pub mod console;
 pub mod mmio;
+pub mod p9;
 pub mod queue;
+pub mod rng;
 
 /// Virtio vendor ID (Red Hat, Inc.)
 pub const VIRTIO_VENDOR_ID: u16 = 0x1AF4;
@@ -60,7 +66,25 @@ pub const VIRTIO_BLK_DEVICE_ID_LEGACY: u16 = 0x1001;
 /// Modern device ID (virtio 1.0+, transitional)
 pub const VIRTIO_BLK_DEVICE_ID_MODERN: u16 = 0x1042;
 
-/// Unified transport enum for virtio-blk
+/// Virtio-rng PCI device IDs
+/// Legacy device ID (virtio 0.9 / transitional)
+pub const VIRTIO_RNG_DEVICE_ID_LEGACY: u16 = 0x1005;
+/// Modern device ID (virtio 1.0+)
+pub const VIRTIO_RNG_DEVICE_ID_MODERN: u16 = 0x1044;
+
+/// Virtio-console PCI device IDs
+/// Legacy device ID (virtio 0.9 / transitional)
+pub const VIRTIO_CONSOLE_DEVICE_ID_LEGACY: u16 = 0x1003;
+/// Modern device ID (virtio 1.0+)
+pub const VIRTIO_CONSOLE_DEVICE_ID_MODERN: u16 = 0x1043;
+
+/// Virtio-9p PCI device IDs
+/// Legacy device ID (virtio 0.9 / transitional)
+pub const VIRTIO_9P_DEVICE_ID_LEGACY: u16 = 0x1009;
+/// Modern device ID (virtio 1.0+)
+pub const VIRTIO_9P_DEVICE_ID_MODERN: u16 = 0x1049;
+
+/// Unified transport enum for virtio device drivers
 #[derive(Debug, Clone, Copy)]
 pub enum VirtioTransport {
     Pci(VirtioPciTransport),
@@ -145,6 +169,20 @@ impl VirtioTransport {
         }
     }
 
+    pub fn read_device_config_u8(&self, offset: u16) -> u8 {
+        match self {
+            Self::Pci(p) => p.read_device_config_u8(offset),
+            Self::Mmio(m) => m.read_config_u8(offset as usize),
+        }
+    }
+
+    pub fn read_device_config_u32(&self, offset: u16) -> u32 {
+        match self {
+            Self::Pci(p) => p.read_device_config_u32(offset),
+            Self::Mmio(m) => m.read_config_u32(offset as usize),
+        }
+    }
+
     pub fn read_device_config_u64(&self, offset: u16) -> u64 {
         match self {
             Self::Pci(p) => p.read_device_config_u64(offset),
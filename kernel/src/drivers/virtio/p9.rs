@@ -0,0 +1,366 @@
+//! Virtio-9p (9P transport) device driver
+//!
+//! Implements the virtio 9P transport: a single request queue (queue 0)
+//! on which each request is a chain of one device-readable buffer holding
+//! a 9P T-message and one device-writable buffer for the R-message. The
+//! device configuration space holds the mount tag the host gave the
+//! export. The protocol itself lives in [`crate::fs::p9`]; this driver only
+//! carries its messages, one request at a time.
+//!
+//! # QEMU usage
+//!
+//! ```text
+//! -virtfs local,path=/path/to/share,mount_tag=host,security_model=none
+//! ```
+//!
+//! and in the guest: `mount host /mnt/host 9p`.
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{self, Ordering};
+
+use spin::Mutex;
+
+use super::{
+    blk::phys_to_kernel_virt,
+    queue::{VirtQueue, VIRTQ_DESC_F_NEXT, VIRTQ_DESC_F_WRITE},
+    VirtioPciTransport, VirtioTransport,
+};
+use crate::{
+    error::KernelError,
+    mm::{FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
+};
+
+/// Virtio device type for 9P transports (virtio spec 5.x, "9P transport")
+const VIRTIO_ID_9P: u32 = 9;
+
+/// Feature bit: the configuration space holds a mount tag
+const VIRTIO_9P_MOUNT_TAG: u32 = 1 << 0;
+
+/// Feature bit 32 (VIRTIO_F_VERSION_1), accepted from version 2 MMIO
+/// devices
+const VIRTIO_F_VERSION_1_HIGH: u32 = 1 << 0;
+
+/// DMA frames for each of the request and reply buffers
+const MSG_FRAMES: usize = 2;
+
+/// Largest 9P message carried, in either direction
+pub const MSIZE: u32 = (MSG_FRAMES * FRAME_SIZE) as u32;
+
+/// How long to wait for the host to answer a request
+const REQUEST_TIMEOUT_MS: u64 = 10_000;
+
+/// Virtio 9P transport device.
+///
+/// Owns the request virtqueue and `2 * MSG_FRAMES` contiguous DMA frames:
+/// the request buffer followed by the reply buffer.
+pub struct Virtio9pDevice {
+    /// Transport handle (PCI or MMIO)
+    transport: VirtioTransport,
+    /// Request virtqueue (queue index 0)
+    queue: VirtQueue,
+    /// First DMA frame
+    frames: FrameNumber,
+    /// Physical address of the request buffer; the reply buffer follows
+    buffer_phys: u64,
+    /// Kernel virtual address of the request buffer
+    buffer_virt: usize,
+    /// Mount tag of the export
+    tag: String,
+}
+
+impl Virtio9pDevice {
+    /// Probe and initialize a virtio-9p device at the given PCI BAR0 I/O
+    /// base.
+    pub fn new(io_base: u16) -> Result<Self, KernelError> {
+        Self::setup(VirtioTransport::Pci(VirtioPciTransport::new(io_base)))
+    }
+
+    /// Probe and initialize a virtio-9p device behind an MMIO transport
+    /// (used on AArch64/RISC-V).
+    pub fn from_mmio(
+        transport: crate::drivers::virtio::mmio::VirtioMmioTransport,
+    ) -> Result<Self, KernelError> {
+        if !transport.matches_device(VIRTIO_ID_9P) {
+            return Err(KernelError::HardwareError {
+                device: "virtio-9p-mmio",
+                code: 0xdead0001,
+            });
+        }
+        Self::setup(VirtioTransport::Mmio(transport))
+    }
+
+    /// Run the common virtio initialization sequence and read the mount
+    /// tag.
+    fn setup(transport: VirtioTransport) -> Result<Self, KernelError> {
+        transport.begin_init();
+
+        let accepted = transport.read_device_features() & VIRTIO_9P_MOUNT_TAG;
+        transport.write_guest_features(accepted);
+        if let VirtioTransport::Mmio(m) = &transport {
+            if m.version() >= 2 {
+                let _ = m.read_device_features_high();
+                m.write_driver_features_high(VIRTIO_F_VERSION_1_HIGH);
+            }
+        }
+        let features_ok = transport.set_features_ok();
+        if !features_ok && matches!(transport, VirtioTransport::Mmio(_)) {
+            // Legacy PCI devices may ignore FEATURES_OK; MMIO must accept it
+            return Err(KernelError::HardwareError {
+                device: "virtio-9p",
+                code: 0x04,
+            });
+        }
+        if accepted == 0 {
+            // Without a tag there is no way to name the export in mount
+            return Err(KernelError::HardwareError {
+                device: "virtio-9p",
+                code: 0x05,
+            });
+        }
+
+        // struct virtio_9p_config { le16 tag_len; u8 tag[]; }
+        let tag_len = u16::from_le_bytes([
+            transport.read_device_config_u8(0),
+            transport.read_device_config_u8(1),
+        ]);
+        let tag: Vec<u8> = (0..tag_len)
+            .map(|i| transport.read_device_config_u8(2 + i))
+            .collect();
+        let tag = String::from_utf8(tag).map_err(|_| KernelError::HardwareError {
+            device: "virtio-9p",
+            code: 0x06, // Mount tag is not UTF-8
+        })?;
+
+        transport.select_queue(0);
+        let queue_size = transport.read_queue_size();
+        if queue_size == 0 {
+            return Err(KernelError::HardwareError {
+                device: "virtio-9p",
+                code: 0x01, // Queue size is zero -- no queue available
+            });
+        }
+
+        let queue = VirtQueue::new(queue_size)?;
+        if let VirtioTransport::Mmio(m) = &transport {
+            m.set_queue_size(queue.size());
+        }
+        transport.write_queue_address(queue.pfn());
+        transport.write_queue_phys(queue.phys_desc(), queue.phys_avail(), queue.phys_used());
+        transport.set_queue_ready();
+
+        let frames = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(2 * MSG_FRAMES, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: 2 * MSG_FRAMES * FRAME_SIZE,
+                available: 0,
+            })?;
+        let buffer_phys = frames.as_u64() * FRAME_SIZE as u64;
+        let buffer_virt = phys_to_kernel_virt(buffer_phys);
+
+        transport.set_driver_ok();
+
+        Ok(Self {
+            transport,
+            queue,
+            frames,
+            buffer_phys,
+            buffer_virt,
+            tag,
+        })
+    }
+
+    /// Mount tag of the export.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Send the 9P message `request` and wait for the reply message.
+    pub fn transact(&mut self, request: &[u8]) -> Result<Vec<u8>, KernelError> {
+        if request.len() > MSIZE as usize {
+            return Err(KernelError::InvalidArgument {
+                name: "request",
+                value: "larger than msize",
+            });
+        }
+
+        let desc_out = self
+            .queue
+            .alloc_desc()
+            .ok_or(KernelError::ResourceExhausted {
+                resource: "virtio-9p descriptors",
+            })?;
+        let Some(desc_in) = self.queue.alloc_desc() else {
+            self.queue.free_desc(desc_out);
+            return Err(KernelError::ResourceExhausted {
+                resource: "virtio-9p descriptors",
+            });
+        };
+
+        // SAFETY: both descriptors are allocated. The request buffer is the
+        // first MSIZE bytes of our DMA frames and the reply buffer the
+        // next MSIZE; request.len() <= MSIZE. The device holds no buffer
+        // of ours: each request waits for the previous one to complete.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                request.as_ptr(),
+                self.buffer_virt as *mut u8,
+                request.len(),
+            );
+            self.queue.write_desc(
+                desc_out,
+                self.buffer_phys,
+                request.len() as u32,
+                VIRTQ_DESC_F_NEXT,
+                desc_in,
+            );
+            self.queue.write_desc(
+                desc_in,
+                self.buffer_phys + MSIZE as u64,
+                MSIZE,
+                VIRTQ_DESC_F_WRITE,
+                0,
+            );
+        }
+
+        atomic::fence(Ordering::Release);
+        self.queue.push_avail(desc_out);
+        self.transport.notify_queue(0);
+
+        // The host serves requests from its own threads, so give it time:
+        // a walk or read of a large directory can take a while.
+        let start = crate::arch::timer::get_timestamp_ms();
+        while !self.queue.has_used() {
+            core::hint::spin_loop();
+            if crate::arch::timer::get_timestamp_ms().saturating_sub(start) > REQUEST_TIMEOUT_MS {
+                // The device still owns the chain; leak it rather than
+                // reuse buffers it may yet write.
+                return Err(KernelError::Timeout {
+                    operation: "virtio-9p request",
+                    duration_ms: REQUEST_TIMEOUT_MS,
+                });
+            }
+        }
+
+        let (_used_id, used_len) = self.queue.poll_used().ok_or(KernelError::HardwareError {
+            device: "virtio-9p",
+            code: 0x02, // Used ring empty after has_used() returned true
+        })?;
+        self.queue.free_chain(desc_out);
+
+        // The reply's own size field bounds it more tightly than used_len,
+        // which some devices report for the whole chain.
+        let reply_virt = self.buffer_virt + MSIZE as usize;
+        // SAFETY: the device returned the chain, so it is done writing the
+        // reply buffer, which is MSIZE bytes of our DMA frames.
+        let reply = unsafe { core::slice::from_raw_parts(reply_virt as *const u8, MSIZE as usize) };
+        let size = u32::from_le_bytes([reply[0], reply[1], reply[2], reply[3]]) as usize;
+        let len = size.min(used_len as usize).min(MSIZE as usize);
+        Ok(Vec::from(&reply[..len]))
+    }
+}
+
+impl Drop for Virtio9pDevice {
+    fn drop(&mut self) {
+        let _ = FRAME_ALLOCATOR
+            .lock()
+            .free_frames(self.frames, 2 * MSG_FRAMES);
+    }
+}
+
+/// A virtio-9p device shared by the filesystems mounted from it.
+pub struct Virtio9pChannel {
+    tag: String,
+    device: Mutex<Virtio9pDevice>,
+}
+
+impl crate::fs::p9::Transport for Virtio9pChannel {
+    fn msize(&self) -> u32 {
+        MSIZE
+    }
+
+    fn rpc(&self, request: &[u8]) -> Result<Vec<u8>, KernelError> {
+        self.device.lock().transact(request)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global driver instances
+// ---------------------------------------------------------------------------
+
+/// Initialized virtio-9p devices.
+static DEVICES: Mutex<Vec<Arc<Virtio9pChannel>>> = Mutex::new(Vec::new());
+
+/// Add an initialized device, unless its tag is already taken.
+fn register(device: Virtio9pDevice) -> Result<String, KernelError> {
+    let tag = String::from(device.tag());
+    let mut devices = DEVICES.lock();
+    if devices.iter().any(|channel| channel.tag == tag) {
+        return Err(KernelError::AlreadyExists {
+            resource: "9p mount tag",
+            id: 0,
+        });
+    }
+    devices.push(Arc::new(Virtio9pChannel {
+        tag: tag.clone(),
+        device: Mutex::new(device),
+    }));
+    Ok(tag)
+}
+
+/// Attach the driver to a virtio-9p PCI function.
+///
+/// Fails if the device has no I/O BAR0, does not initialize, or exports
+/// under a mount tag another device already uses.
+#[cfg(target_arch = "x86_64")]
+pub fn attach_pci(device: &crate::drivers::pci::PciDevice) -> Result<(), KernelError> {
+    let io_base = device
+        .bars
+        .first()
+        .and_then(|bar| bar.get_io_address())
+        .ok_or(KernelError::HardwareError {
+            device: "virtio-9p",
+            code: 0xdead0010,
+        })? as u16;
+
+    super::blk::enable_bus_master(device);
+
+    let _tag = register(Virtio9pDevice::new(io_base)?)?;
+    crate::println!(
+        "[VIRTIO-9P] Mount tag '{}' at {}:{}:{}",
+        _tag,
+        device.location.bus,
+        device.location.device,
+        device.location.function,
+    );
+    Ok(())
+}
+
+/// Attach the driver to the virtio-mmio device at `base`.
+///
+/// Fails if there is no virtio-9p device at `base`, it does not initialize,
+/// or it exports under a mount tag another device already uses.
+pub fn attach_mmio(base: usize) -> Result<(), KernelError> {
+    let device = Virtio9pDevice::from_mmio(super::mmio::VirtioMmioTransport::new(base))?;
+    let _tag = register(device)?;
+    crate::println!("[VIRTIO-9P/MMIO] Mount tag '{}' at base {:#x}", _tag, base);
+    Ok(())
+}
+
+/// The device exporting under mount tag `tag`.
+pub fn get(tag: &str) -> Option<Arc<Virtio9pChannel>> {
+    DEVICES
+        .lock()
+        .iter()
+        .find(|channel| channel.tag == tag)
+        .cloned()
+}
+
+/// Mount tags of every virtio-9p device.
+pub fn tags() -> Vec<String> {
+    DEVICES
+        .lock()
+        .iter()
+        .map(|channel| channel.tag.clone())
+        .collect()
+}
@@ -0,0 +1,318 @@
+//! Virtio-rng (entropy source) device driver
+//!
+//! Implements the virtio entropy device described in the virtio
+//! specification, section 5.4. The device has a single request queue
+//! (queue 0); the driver posts device-writable buffers and the host fills
+//! them with random bytes taken from its own entropy source.
+//!
+//! Output is never handed to callers directly -- it is mixed into the kernel
+//! CSPRNG via [`crate::crypto::random::add_entropy`] at probe time and on
+//! every reseed (see [`read_entropy`]).
+//!
+//! # QEMU usage
+//!
+//! ```text
+//! -object rng-random,filename=/dev/urandom,id=rng0 -device virtio-rng-pci,rng=rng0
+//! ```
+
+use core::sync::atomic::{self, Ordering};
+
+use spin::Mutex;
+
+use super::{
+    blk::phys_to_kernel_virt,
+    queue::{VirtQueue, VIRTQ_DESC_F_WRITE},
+    VirtioPciTransport, VirtioTransport,
+};
+use crate::{
+    error::KernelError,
+    mm::{FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
+    sync::once_lock::OnceLock,
+};
+
+/// Virtio device type for entropy sources (virtio spec 5.4)
+const VIRTIO_ID_RNG: u32 = 4;
+
+/// Largest single request posted to the device
+const MAX_REQUEST: usize = 256;
+
+/// Bytes pulled from the device to seed the CSPRNG at probe time
+const PROBE_SEED_BYTES: usize = 64;
+
+/// Virtio entropy device.
+///
+/// Owns one request virtqueue and a single DMA frame that the device writes
+/// random bytes into.
+pub struct VirtioRngDevice {
+    /// Transport handle (PCI or MMIO)
+    transport: VirtioTransport,
+    /// Request virtqueue (queue index 0)
+    queue: VirtQueue,
+    /// DMA frame backing the receive buffer
+    frame: FrameNumber,
+    /// Physical address of the receive buffer
+    buffer_phys: u64,
+    /// Kernel virtual address of the receive buffer
+    buffer_virt: usize,
+}
+
+impl VirtioRngDevice {
+    /// Probe and initialize a virtio-rng device at the given PCI BAR0 I/O
+    /// base.
+    pub fn new(io_base: u16) -> Result<Self, KernelError> {
+        Self::setup(VirtioTransport::Pci(VirtioPciTransport::new(io_base)))
+    }
+
+    /// Probe and initialize a virtio-rng device behind an MMIO transport
+    /// (used on AArch64/RISC-V).
+    pub fn from_mmio(
+        transport: crate::drivers::virtio::mmio::VirtioMmioTransport,
+    ) -> Result<Self, KernelError> {
+        if !transport.matches_device(VIRTIO_ID_RNG) {
+            return Err(KernelError::HardwareError {
+                device: "virtio-rng-mmio",
+                code: 0xdead0001,
+            });
+        }
+        Self::setup(VirtioTransport::Mmio(transport))
+    }
+
+    /// Run the common virtio initialization sequence. The entropy device
+    /// defines no feature bits and no configuration space, so only queue 0
+    /// needs to be set up.
+    fn setup(transport: VirtioTransport) -> Result<Self, KernelError> {
+        // Reset + ACKNOWLEDGE + DRIVER
+        transport.begin_init();
+
+        // No device-specific features to negotiate
+        let _ = transport.read_device_features();
+        transport.write_guest_features(0);
+        let features_ok = transport.set_features_ok();
+        if !features_ok && matches!(transport, VirtioTransport::Mmio(_)) {
+            // Legacy PCI devices may ignore FEATURES_OK; MMIO must accept it
+            return Err(KernelError::HardwareError {
+                device: "virtio-rng",
+                code: 0x04,
+            });
+        }
+
+        transport.select_queue(0);
+        let queue_size = transport.read_queue_size();
+        if queue_size == 0 {
+            return Err(KernelError::HardwareError {
+                device: "virtio-rng",
+                code: 0x01, // Queue size is zero -- no queue available
+            });
+        }
+
+        let queue = VirtQueue::new(queue_size)?;
+        if let VirtioTransport::Mmio(m) = &transport {
+            m.set_queue_size(queue.size());
+        }
+        transport.write_queue_address(queue.pfn());
+        transport.write_queue_phys(queue.phys_desc(), queue.phys_avail(), queue.phys_used());
+        transport.set_queue_ready();
+
+        let frame = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(1, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: FRAME_SIZE,
+                available: 0,
+            })?;
+        let buffer_phys = frame.as_u64() * FRAME_SIZE as u64;
+        let buffer_virt = phys_to_kernel_virt(buffer_phys);
+
+        transport.set_driver_ok();
+
+        Ok(Self {
+            transport,
+            queue,
+            frame,
+            buffer_phys,
+            buffer_virt,
+        })
+    }
+
+    /// Fill (a prefix of) `buf` with bytes from the device.
+    ///
+    /// Requests at most [`MAX_REQUEST`] bytes and polls for completion.
+    /// Returns the number of bytes the device actually supplied, which may
+    /// be less than requested.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, KernelError> {
+        let len = buf.len().min(MAX_REQUEST);
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let desc = self
+            .queue
+            .alloc_desc()
+            .ok_or(KernelError::ResourceExhausted {
+                resource: "virtio-rng descriptors",
+            })?;
+
+        // SAFETY: desc is a valid allocated descriptor index. buffer_phys
+        // points to our DMA frame, which is at least MAX_REQUEST bytes.
+        unsafe {
+            self.queue
+                .write_desc(desc, self.buffer_phys, len as u32, VIRTQ_DESC_F_WRITE, 0);
+        }
+
+        // Ensure the descriptor is visible before notifying
+        atomic::fence(Ordering::Release);
+        self.queue.push_avail(desc);
+        self.transport.notify_queue(0);
+
+        let mut spins: u32 = 0;
+        const MAX_SPINS: u32 = 10_000_000;
+        while !self.queue.has_used() {
+            core::hint::spin_loop();
+            spins += 1;
+            if spins >= MAX_SPINS {
+                self.queue.free_chain(desc);
+                return Err(KernelError::Timeout {
+                    operation: "virtio-rng request",
+                    duration_ms: 0,
+                });
+            }
+        }
+
+        let (_used_id, used_len) = self.queue.poll_used().ok_or(KernelError::HardwareError {
+            device: "virtio-rng",
+            code: 0x02, // Used ring empty after has_used() returned true
+        })?;
+        self.queue.free_chain(desc);
+
+        let filled = (used_len as usize).min(len);
+        // SAFETY: buffer_virt maps our DMA frame; the device has finished
+        // writing `filled` bytes (it returned the descriptor via the used
+        // ring), and `filled <= buf.len()`.
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.buffer_virt as *const u8, buf.as_mut_ptr(), filled);
+        }
+        Ok(filled)
+    }
+}
+
+impl Drop for VirtioRngDevice {
+    fn drop(&mut self) {
+        let _ = FRAME_ALLOCATOR.lock().free_frames(self.frame, 1);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global driver instance and initialization
+// ---------------------------------------------------------------------------
+
+/// Global virtio-rng device instance (if a device was found and initialized).
+static VIRTIO_RNG: OnceLock<Mutex<VirtioRngDevice>> = OnceLock::new();
+
+/// Probe for a virtio-rng device and, if one is found, seed the kernel
+/// CSPRNG from it.
+pub fn init() {
+    #[cfg(target_arch = "x86_64")]
+    init_x86_64();
+
+    #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+    init_mmio();
+
+    let mut seed = [0u8; PROBE_SEED_BYTES];
+    let n = read_entropy(&mut seed);
+    if n > 0 {
+        crate::crypto::random::add_entropy(&seed[..n]);
+        crate::println!("[VIRTIO-RNG] Mixed {} bytes into the CSPRNG", n);
+    }
+}
+
+/// x86_64 PCI-based virtio-rng initialization.
+#[cfg(target_arch = "x86_64")]
+fn init_x86_64() {
+    use crate::drivers::pci;
+
+    if !pci::is_pci_initialized() {
+        return;
+    }
+
+    let all_devices = pci::get_pci_bus().lock().get_all_devices();
+
+    for device in &all_devices {
+        if device.vendor_id != super::VIRTIO_VENDOR_ID
+            || (device.device_id != super::VIRTIO_RNG_DEVICE_ID_LEGACY
+                && device.device_id != super::VIRTIO_RNG_DEVICE_ID_MODERN)
+        {
+            continue;
+        }
+
+        let io_base = match device.bars.first().and_then(|bar| bar.get_io_address()) {
+            Some(addr) => addr as u16,
+            None => {
+                crate::println!("[VIRTIO-RNG] BAR0 is not an I/O BAR, skipping device");
+                continue;
+            }
+        };
+
+        super::blk::enable_bus_master(device);
+
+        match VirtioRngDevice::new(io_base) {
+            Ok(dev) => {
+                let _ = VIRTIO_RNG.set(Mutex::new(dev));
+                crate::println!(
+                    "[VIRTIO-RNG] Initialized device at {}:{}:{}",
+                    device.location.bus,
+                    device.location.device,
+                    device.location.function,
+                );
+            }
+            Err(e) => {
+                crate::println!("[VIRTIO-RNG] Failed to initialize device: {:?}", e);
+            }
+        }
+
+        // One entropy device is plenty
+        return;
+    }
+}
+
+/// AArch64 / RISC-V virtio-mmio initialization.
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+fn init_mmio() {
+    use crate::drivers::virtio::mmio::{device_bases, VirtioMmioTransport};
+
+    for base in device_bases() {
+        if let Ok(dev) = VirtioRngDevice::from_mmio(VirtioMmioTransport::new(base)) {
+            if VIRTIO_RNG.set(Mutex::new(dev)).is_ok() {
+                crate::println!("[VIRTIO-RNG/MMIO] Device initialized at base {:#x}", base);
+                return;
+            }
+        }
+    }
+}
+
+/// Fill `dest` from the virtio-rng device, if one is present.
+///
+/// Returns the number of bytes written, which is 0 when no device was found,
+/// the device is busy elsewhere, or the request failed. Never blocks on the
+/// device lock, so it is safe to call from the CSPRNG reseed path.
+pub fn read_entropy(dest: &mut [u8]) -> usize {
+    let Some(device) = VIRTIO_RNG.get() else {
+        return 0;
+    };
+    let Some(mut device) = device.try_lock() else {
+        return 0;
+    };
+
+    let mut filled = 0;
+    while filled < dest.len() {
+        match device.read(&mut dest[filled..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => filled += n,
+        }
+    }
+    filled
+}
+
+/// Check if a virtio-rng device has been initialized.
+pub fn is_initialized() -> bool {
+    VIRTIO_RNG.get().is_some()
+}
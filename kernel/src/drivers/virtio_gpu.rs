@@ -23,6 +23,7 @@
 //! - Host transfer (TRANSFER_TO_HOST_2D)
 //! - Display flush (RESOURCE_FLUSH)
 //! - EDID query (GET_EDID, if supported)
+//! - Hardware cursor (UPDATE_CURSOR / MOVE_CURSOR on the cursor queue)
 
 // Allow dead code for VirtIO GPU protocol constants, structures, and methods
 // not yet fully exercised by callers during Phase 7 bringup.
@@ -30,6 +31,7 @@
 
 use alloc::vec::Vec;
 
+use super::driver_common::DriverCell;
 use crate::error::KernelError;
 
 // ============================================================================
@@ -60,6 +62,10 @@ const VIRTIO_GPU_CMD_GET_CAPSET_INFO: u32 = 0x108;
 const VIRTIO_GPU_CMD_GET_CAPSET: u32 = 0x109;
 /// Get EDID data for a scanout
 const VIRTIO_GPU_CMD_GET_EDID: u32 = 0x10A;
+/// Set the cursor image and position (cursor queue)
+const VIRTIO_GPU_CMD_UPDATE_CURSOR: u32 = 0x300;
+/// Move the cursor without changing its image (cursor queue)
+const VIRTIO_GPU_CMD_MOVE_CURSOR: u32 = 0x301;
 
 // --- Response types ---
 
@@ -108,6 +114,13 @@ const VIRTIO_GPU_F_EDID: u64 = 1 << 1;
 // --- Max scanouts per the spec ---
 const VIRTIO_GPU_MAX_SCANOUTS: usize = 16;
 
+/// Cursor resources are always 64x64 per the spec
+pub const CURSOR_RESOURCE_SIZE: u32 = 64;
+
+/// Framebuffers allocated for page flipping, including the one on scanout
+/// (3 = triple buffering)
+pub const SWAPCHAIN_BUFFERS: usize = 3;
+
 // ============================================================================
 // VirtIO MMIO Register Offsets (modern interface, matches virtio_net.rs)
 // ============================================================================
@@ -339,6 +352,41 @@ struct VirtioGpuResourceDetachBacking {
     padding: u32,
 }
 
+/// Cursor position, shared by UPDATE_CURSOR and MOVE_CURSOR.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct VirtioGpuCursorPos {
+    /// Scanout the cursor is shown on
+    scanout_id: u32,
+    /// X coordinate of the hot spot
+    x: u32,
+    /// Y coordinate of the hot spot
+    y: u32,
+    /// Padding
+    padding: u32,
+}
+
+/// UPDATE_CURSOR / MOVE_CURSOR command structure.
+///
+/// MOVE_CURSOR only looks at `pos`; a `resource_id` of 0 in UPDATE_CURSOR
+/// hides the cursor.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct VirtioGpuUpdateCursor {
+    /// Command header
+    hdr: VirtioGpuCtrlHdr,
+    /// New cursor position
+    pos: VirtioGpuCursorPos,
+    /// 64x64 resource holding the cursor image
+    resource_id: u32,
+    /// Hot spot X within the image
+    hot_x: u32,
+    /// Hot spot Y within the image
+    hot_y: u32,
+    /// Padding
+    padding: u32,
+}
+
 /// GET_EDID command structure.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -527,6 +575,12 @@ struct DataBuffer {
     phys_addr: u64,
 }
 
+/// A framebuffer resource that is waiting to be flipped onto the scanout.
+struct SwapBuffer {
+    resource_id: u32,
+    backing: Vec<u32>,
+}
+
 // ============================================================================
 // VirtIO GPU Driver State
 // ============================================================================
@@ -580,6 +634,14 @@ pub struct VirtioGpuDriver {
     framebuffer_resource_id: u32,
     /// Backing pixel buffer for the framebuffer resource
     framebuffer_backing: Option<Vec<u32>>,
+    /// Framebuffers not on scanout, oldest first; `flip()` presents the
+    /// first one
+    back_buffers: Vec<SwapBuffer>,
+
+    /// 64x64 resource holding the hardware cursor image (0 = none yet)
+    cursor_resource_id: u32,
+    /// Backing pixel buffer for the cursor resource
+    cursor_backing: Option<Vec<u32>>,
 
     /// Display width in pixels
     width: u32,
@@ -604,6 +666,9 @@ impl VirtioGpuDriver {
             next_resource_id: 1,
             framebuffer_resource_id: 0,
             framebuffer_backing: None,
+            back_buffers: Vec::new(),
+            cursor_resource_id: 0,
+            cursor_backing: None,
             width: 0,
             height: 0,
         };
@@ -762,6 +827,9 @@ impl VirtioGpuDriver {
                 if let Err(e) = self.setup_framebuffer() {
                     crate::println!("[VIRTIO-GPU] Framebuffer setup failed: {:?}", e);
                     // Non-fatal: driver is still usable for manual operations
+                } else if let Err(e) = self.setup_swapchain(SWAPCHAIN_BUFFERS) {
+                    crate::println!("[VIRTIO-GPU] Swapchain setup failed: {:?}", e);
+                    // Non-fatal: flip() falls back to flushing in place
                 }
             }
             Err(e) => {
@@ -1043,6 +1111,79 @@ impl VirtioGpuDriver {
         }
     }
 
+    /// Send a command via the cursor queue.
+    ///
+    /// Cursor commands have no response payload: the request goes out as a
+    /// single device-readable descriptor and the used ring is polled only to
+    /// reclaim it, so the descriptor is free for the next pointer motion.
+    fn send_cursor_command(&mut self, cmd: &VirtioGpuUpdateCursor) -> Result<(), KernelError> {
+        let mmio = self.mmio_base;
+
+        let cursorq = self.cursorq.as_mut().ok_or(KernelError::NotImplemented {
+            feature: "virtio_gpu_cursor_queue",
+        })?;
+
+        let desc_idx = cursorq.alloc_desc().ok_or(KernelError::ResourceExhausted {
+            resource: "virtio_gpu_cursor_descriptors",
+        })?;
+
+        let Some(buf) = self.cursor_buffers.get(desc_idx as usize) else {
+            cursorq.free_desc(desc_idx);
+            return Err(KernelError::ResourceExhausted {
+                resource: "virtio_gpu_cursor_buffers",
+            });
+        };
+
+        let len = core::mem::size_of::<VirtioGpuUpdateCursor>();
+        // SAFETY: buf.virt_addr points to a leaked 4096-byte allocation and
+        // the command is far smaller. We hold &mut self so no concurrent
+        // access; the #[repr(C)] command is copied byte-for-byte.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                cmd as *const VirtioGpuUpdateCursor as *const u8,
+                buf.virt_addr as *mut u8,
+                len,
+            );
+        }
+
+        cursorq.descriptors[desc_idx as usize] = VirtqDesc {
+            addr: buf.phys_addr,
+            len: len as u32,
+            flags: 0,
+            next: 0,
+        };
+        cursorq.add_to_avail(desc_idx);
+
+        // Kick the device (cursor queue = index 1)
+        // SAFETY: Writing to VirtIO queue notify register.
+        unsafe {
+            core::ptr::write_volatile((mmio + VIRTIO_MMIO_QUEUE_NOTIFY) as *mut u32, 1);
+        }
+
+        let mut timeout = 1_000_000u32;
+        loop {
+            if let Some((used_id, _len)) = cursorq.get_used() {
+                cursorq.free_desc(used_id);
+                if used_id == desc_idx {
+                    return Ok(());
+                }
+                continue;
+            }
+
+            timeout -= 1;
+            if timeout == 0 {
+                // The device still owns the descriptor; leave it allocated
+                // and let a later completion reclaim it.
+                return Err(KernelError::Timeout {
+                    operation: "virtio_gpu_cursor_command",
+                    duration_ms: 1000,
+                });
+            }
+
+            core::hint::spin_loop();
+        }
+    }
+
     /// Send a typed command and expect a simple OK_NODATA response.
     fn send_simple_command<T: Sized>(&mut self, cmd: &T) -> Result<(), KernelError> {
         // SAFETY: Reinterpreting a #[repr(C)] struct as a byte slice for
@@ -1448,6 +1589,109 @@ impl VirtioGpuDriver {
         self.send_simple_command(&cmd)
     }
 
+    // ---- Hardware cursor ----
+
+    /// Check whether the device has a cursor queue (hardware cursor plane).
+    pub fn has_cursor_plane(&self) -> bool {
+        self.cursorq.is_some()
+    }
+
+    /// Upload a cursor image and show it with its hot spot at `(x, y)`.
+    ///
+    /// `pixels` is `width * height` ARGB words (B8G8R8A8 in memory); images
+    /// larger than the 64x64 cursor resource are clipped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_cursor_image(
+        &mut self,
+        pixels: &[u32],
+        width: u32,
+        height: u32,
+        hot_x: u32,
+        hot_y: u32,
+        x: u32,
+        y: u32,
+    ) -> Result<(), KernelError> {
+        if !self.has_cursor_plane() {
+            return Err(KernelError::NotImplemented {
+                feature: "virtio_gpu_cursor_queue",
+            });
+        }
+
+        let size = CURSOR_RESOURCE_SIZE;
+        if self.cursor_resource_id == 0 {
+            let resource_id = self.alloc_resource_id();
+            self.create_resource_2d(resource_id, FORMAT_B8G8R8A8_UNORM, size, size)?;
+            let backing = alloc::vec![0u32; (size * size) as usize];
+            self.attach_backing(resource_id, backing.as_ptr() as u64, size * size * 4)?;
+            self.cursor_resource_id = resource_id;
+            self.cursor_backing = Some(backing);
+        }
+
+        if let Some(backing) = self.cursor_backing.as_mut() {
+            backing.fill(0);
+            let copy_w = width.min(size) as usize;
+            for row in 0..height.min(size) as usize {
+                let src = row * width as usize;
+                let dst = row * size as usize;
+                backing[dst..dst + copy_w].copy_from_slice(&pixels[src..src + copy_w]);
+            }
+        }
+
+        let rect = VirtioGpuRect::new(0, 0, size, size);
+        self.transfer_to_host_2d(self.cursor_resource_id, rect)?;
+
+        let cmd = VirtioGpuUpdateCursor {
+            hdr: VirtioGpuCtrlHdr::new(VIRTIO_GPU_CMD_UPDATE_CURSOR),
+            pos: VirtioGpuCursorPos {
+                scanout_id: 0,
+                x,
+                y,
+                padding: 0,
+            },
+            resource_id: self.cursor_resource_id,
+            hot_x: hot_x.min(size - 1),
+            hot_y: hot_y.min(size - 1),
+            padding: 0,
+        };
+        self.send_cursor_command(&cmd)
+    }
+
+    /// Move the hardware cursor so its hot spot is at `(x, y)`.
+    pub fn move_cursor(&mut self, x: u32, y: u32) -> Result<(), KernelError> {
+        let cmd = VirtioGpuUpdateCursor {
+            hdr: VirtioGpuCtrlHdr::new(VIRTIO_GPU_CMD_MOVE_CURSOR),
+            pos: VirtioGpuCursorPos {
+                scanout_id: 0,
+                x,
+                y,
+                padding: 0,
+            },
+            resource_id: 0,
+            hot_x: 0,
+            hot_y: 0,
+            padding: 0,
+        };
+        self.send_cursor_command(&cmd)
+    }
+
+    /// Hide the hardware cursor.
+    pub fn hide_cursor(&mut self) -> Result<(), KernelError> {
+        let cmd = VirtioGpuUpdateCursor {
+            hdr: VirtioGpuCtrlHdr::new(VIRTIO_GPU_CMD_UPDATE_CURSOR),
+            pos: VirtioGpuCursorPos {
+                scanout_id: 0,
+                x: 0,
+                y: 0,
+                padding: 0,
+            },
+            resource_id: 0,
+            hot_x: 0,
+            hot_y: 0,
+            padding: 0,
+        };
+        self.send_cursor_command(&cmd)
+    }
+
     // ---- Framebuffer management ----
 
     /// Set up the primary framebuffer: create a 2D resource, attach a
@@ -1548,6 +1792,107 @@ impl VirtioGpuDriver {
         Ok(())
     }
 
+    /// Allocate spare framebuffer resources for page flipping.
+    ///
+    /// `count` is the total number of buffers including the one on scanout:
+    /// 2 for double buffering, 3 for triple buffering. Buffers that were
+    /// allocated before a failure are kept.
+    pub fn setup_swapchain(&mut self, count: usize) -> Result<(), KernelError> {
+        if self.framebuffer_resource_id == 0 {
+            return Err(KernelError::InvalidState {
+                expected: "framebuffer_setup",
+                actual: "no_framebuffer",
+            });
+        }
+        if count < 2 {
+            return Err(KernelError::InvalidArgument {
+                name: "count",
+                value: "fewer_than_two_buffers",
+            });
+        }
+
+        let pixel_count = (self.width * self.height) as usize;
+        while self.back_buffers.len() + 1 < count {
+            let resource_id = self.alloc_resource_id();
+            self.create_resource_2d(resource_id, FORMAT_B8G8R8X8_UNORM, self.width, self.height)?;
+
+            let backing = alloc::vec![0u32; pixel_count];
+            let backing_addr = backing.as_ptr() as u64;
+            if let Err(e) = self.attach_backing(resource_id, backing_addr, (pixel_count * 4) as u32)
+            {
+                let _ = self.resource_unref(resource_id);
+                return Err(e);
+            }
+
+            self.back_buffers.push(SwapBuffer {
+                resource_id,
+                backing,
+            });
+        }
+
+        crate::println!(
+            "[VIRTIO-GPU] Swapchain ready: {} buffers",
+            self.swapchain_len()
+        );
+        Ok(())
+    }
+
+    /// Number of framebuffers, including the one on scanout.
+    pub fn swapchain_len(&self) -> usize {
+        if self.framebuffer_resource_id == 0 {
+            0
+        } else {
+            1 + self.back_buffers.len()
+        }
+    }
+
+    /// Pixels of the buffer the next `flip()` presents.
+    ///
+    /// Without a swapchain this is the scanout buffer itself, which the
+    /// host may read while it is being drawn.
+    pub fn back_buffer_mut(&mut self) -> Option<&mut [u32]> {
+        match self.back_buffers.first_mut() {
+            Some(buffer) => Some(&mut buffer.backing),
+            None => self.framebuffer_backing.as_deref_mut(),
+        }
+    }
+
+    /// Present the back buffer.
+    ///
+    /// The back buffer is transferred to the host while the current one
+    /// stays on screen, then a single SET_SCANOUT switches the display to
+    /// it, so a frame is never shown half copied. The previous scanout
+    /// buffer becomes the last back buffer. Without a swapchain this is
+    /// `flush_framebuffer()`.
+    pub fn flip(&mut self) -> Result<(), KernelError> {
+        if self.back_buffers.is_empty() {
+            return self.flush_framebuffer();
+        }
+
+        let rect = VirtioGpuRect::new(0, 0, self.width, self.height);
+        let resource_id = self.back_buffers[0].resource_id;
+        self.transfer_to_host_2d(resource_id, rect)?;
+
+        // Not set_scanout(): that logs, and this runs every frame
+        let cmd = VirtioGpuSetScanout {
+            hdr: VirtioGpuCtrlHdr::new(VIRTIO_GPU_CMD_SET_SCANOUT),
+            rect,
+            scanout_id: 0,
+            resource_id,
+        };
+        self.send_simple_command(&cmd)?;
+        self.resource_flush(resource_id, rect)?;
+
+        let next = &mut self.back_buffers[0];
+        core::mem::swap(&mut self.framebuffer_resource_id, &mut next.resource_id);
+        if let Some(ref mut front) = self.framebuffer_backing {
+            core::mem::swap(front, &mut next.backing);
+        }
+        self.back_buffers.rotate_left(1);
+
+        Ok(())
+    }
+
     /// Get mutable access to the framebuffer pixel buffer.
     ///
     /// Returns a slice of BGRX pixels. Modify the pixels, then call
@@ -1802,7 +2147,7 @@ pub fn enumerate_gpu_devices() -> Vec<(u16, u16, u8, u8)> {
 // ============================================================================
 
 /// Global VirtIO GPU driver instance.
-static VIRTIO_GPU: spin::Mutex<Option<VirtioGpuDriver>> = spin::Mutex::new(None);
+static VIRTIO_GPU: DriverCell<Option<VirtioGpuDriver>> = DriverCell::new(None);
 
 /// Initialize the VirtIO GPU driver.
 ///
@@ -1823,7 +2168,7 @@ pub fn init() -> Result<(), KernelError> {
                     driver.height(),
                     driver.framebuffer_resource_id()
                 );
-                *VIRTIO_GPU.lock() = Some(driver);
+                VIRTIO_GPU.set(driver);
                 return Ok(());
             }
             Err(e) => {
@@ -1844,7 +2189,7 @@ pub fn with_driver<R, F: FnOnce(&mut VirtioGpuDriver) -> R>(f: F) -> Option<R> {
 
 /// Check if a VirtIO GPU driver is available and initialized.
 pub fn is_available() -> bool {
-    VIRTIO_GPU.lock().is_some()
+    VIRTIO_GPU.is_set()
 }
 
 /// Flush the VirtIO GPU framebuffer to the display.
@@ -1861,6 +2206,26 @@ pub fn flush_framebuffer() -> Result<(), KernelError> {
     }
 }
 
+/// Check if a VirtIO GPU with a hardware cursor plane is available.
+pub fn has_cursor_plane() -> bool {
+    VIRTIO_GPU
+        .lock()
+        .as_ref()
+        .is_some_and(|d| d.has_cursor_plane())
+}
+
+/// Present the VirtIO GPU back buffer (see [`VirtioGpuDriver::flip`]).
+pub fn flip() -> Result<(), KernelError> {
+    if let Some(ref mut driver) = *VIRTIO_GPU.lock() {
+        driver.flip()
+    } else {
+        Err(KernelError::InvalidState {
+            expected: "virtio_gpu_initialized",
+            actual: "no_driver",
+        })
+    }
+}
+
 /// Get the display dimensions (width, height) if a VirtIO GPU is available.
 pub fn get_display_size() -> Option<(u32, u32)> {
     VIRTIO_GPU.lock().as_ref().map(|d| (d.width(), d.height()))
@@ -1912,6 +2277,13 @@ mod tests {
         assert_eq!(core::mem::size_of::<VirtioGpuMemEntry>(), 16);
     }
 
+    #[test]
+    fn test_update_cursor_size() {
+        // hdr (24) + pos (16) + resource_id (4) + hot_x (4) + hot_y (4) +
+        // padding (4) = 56
+        assert_eq!(core::mem::size_of::<VirtioGpuUpdateCursor>(), 56);
+    }
+
     #[test]
     fn test_response_to_error() {
         let err = VirtioGpuDriver::response_to_error(VIRTIO_GPU_RESP_ERR_OUT_OF_MEMORY);
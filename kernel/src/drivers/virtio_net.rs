@@ -706,6 +706,7 @@ impl NetworkDevice for VirtioNetDriver {
             supports_checksum_offload: (self.features & VIRTIO_NET_F_CSUM) != 0,
             supports_tso: false,
             supports_lro: false,
+            supports_scatter_gather: false,
         }
     }
 
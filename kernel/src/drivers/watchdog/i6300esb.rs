@@ -0,0 +1,158 @@
+//! Intel 6300ESB watchdog timer
+//!
+//! The PCI watchdog QEMU emulates for x86 machines (`-device i6300esb`).
+//! It counts down in two stages at about 1 kHz (the PCI clock divided by
+//! 2^15): the first stage would raise an interrupt, which is left disabled,
+//! and the second resets the machine. Each stage is loaded with half the
+//! timeout.
+//!
+//! The reload register is locked; every write to the timer registers and
+//! every reload has to be preceded by the two-byte unlock sequence.
+
+use crate::drivers::pci::{self, PciBar, PciLocation};
+
+const VENDOR_INTEL: u16 = 0x8086;
+const DEVICE_6300ESB_WDT: u16 = 0x25ab;
+
+// MMIO registers (BAR 0)
+const TIMER1: usize = 0x00;
+const TIMER2: usize = 0x04;
+const RELOAD: usize = 0x0c;
+
+// Configuration space registers
+/// Interrupt type (bits 1:0), clock scale (bit 2), output disable (bit 5).
+const CONFIG_REG: u16 = 0x60;
+/// Lock (bit 0), enable (bit 1), free-running mode (bit 2).
+const LOCK_REG: u16 = 0x68;
+
+/// No interrupt from the first stage, 1 kHz clock, reset output enabled.
+const CONFIG_WATCHDOG: u32 = 0x0003;
+const LOCK_ENABLE: u32 = 1 << 1;
+
+// RELOAD register bits and unlock sequence
+const RELOAD_RELOAD: u16 = 1 << 8;
+const RELOAD_TIMEOUT: u16 = 1 << 9;
+const UNLOCK1: u16 = 0x80;
+const UNLOCK2: u16 = 0x86;
+
+/// Largest stage preload (the timer registers are 20 bits wide).
+const MAX_PRELOAD: u32 = 0xF_FFFF;
+
+/// A probed 6300ESB watchdog.
+#[derive(Debug, Clone, Copy)]
+pub struct Esb {
+    location: PciLocation,
+    /// Virtual address of the BAR 0 registers.
+    base: usize,
+}
+
+/// Find the watchdog on the PCI bus.
+pub fn probe() -> Option<Esb> {
+    if !pci::is_pci_initialized() {
+        return None;
+    }
+    let dev = pci::get_pci_bus()
+        .lock()
+        .find_devices_by_id(VENDOR_INTEL, DEVICE_6300ESB_WDT)
+        .into_iter()
+        .next()?;
+    let Some(PciBar::Memory { address, .. }) = dev.bars.first() else {
+        return None;
+    };
+    pci::enable_memory_space(dev.location);
+    Some(Esb {
+        location: dev.location,
+        base: crate::mm::phys_to_virt_addr(*address) as usize,
+    })
+}
+
+/// Per-stage preload for `timeout_ms`: two stages at ~1 kHz, so 512 ticks
+/// per second of timeout.
+fn preload(timeout_ms: u64) -> u32 {
+    (timeout_ms * 512 / 1000).clamp(1, MAX_PRELOAD as u64) as u32
+}
+
+/// Write `value` to the reload register after the unlock sequence.
+fn reload(base: usize, value: u16) {
+    // SAFETY: `base` is the mapped BAR 0 of the watchdog, which spans the
+    // 16-bit RELOAD register at offset 0x0c.
+    unsafe {
+        let reg = (base + RELOAD) as *mut u16;
+        core::ptr::write_volatile(reg, UNLOCK1);
+        core::ptr::write_volatile(reg, UNLOCK2);
+        core::ptr::write_volatile(reg, value);
+    }
+}
+
+/// Restart the countdown. Only touches MMIO, so it is safe from the timer
+/// interrupt.
+pub fn kick(base: usize) {
+    reload(base, RELOAD_RELOAD);
+}
+
+impl Esb {
+    /// Address [`kick`] takes.
+    pub fn kick_base(&self) -> usize {
+        self.base
+    }
+
+    /// Whether the previous boot ended in a reset by this watchdog. The flag
+    /// is cleared by [`Esb::start`].
+    pub fn caused_reset(&self) -> bool {
+        // SAFETY: RELOAD lies within the mapped BAR 0; reading it has no
+        // side effects.
+        let value = unsafe { core::ptr::read_volatile((self.base + RELOAD) as *const u16) };
+        value & RELOAD_TIMEOUT != 0
+    }
+
+    fn set_lock_reg(&self, value: u32) {
+        let dword = pci::read_config(self.location, LOCK_REG);
+        pci::write_config(self.location, LOCK_REG, (dword & !0xFF) | value);
+    }
+
+    fn write_timer(&self, offset: usize, value: u32) {
+        // SAFETY: As in `reload`; the timer registers are 32 bits wide at
+        // offsets 0x00 and 0x04 of the mapped BAR 0.
+        unsafe {
+            let unlock = (self.base + RELOAD) as *mut u16;
+            core::ptr::write_volatile(unlock, UNLOCK1);
+            core::ptr::write_volatile(unlock, UNLOCK2);
+            core::ptr::write_volatile((self.base + offset) as *mut u32, value);
+        }
+    }
+
+    /// Program `timeout_ms` and start counting down.
+    pub fn start(&self, timeout_ms: u64) {
+        let config = pci::read_config(self.location, CONFIG_REG);
+        pci::write_config(
+            self.location,
+            CONFIG_REG,
+            (config & !0xFFFF) | CONFIG_WATCHDOG,
+        );
+        self.set_lock_reg(0);
+
+        let preload = preload(timeout_ms);
+        self.write_timer(TIMER1, preload);
+        self.write_timer(TIMER2, preload);
+        reload(self.base, RELOAD_RELOAD | RELOAD_TIMEOUT);
+        self.set_lock_reg(LOCK_ENABLE);
+    }
+
+    /// Stop the countdown.
+    pub fn stop(&self) {
+        reload(self.base, RELOAD_RELOAD);
+        self.set_lock_reg(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preload() {
+        assert_eq!(preload(30_000), 15_360);
+        assert_eq!(preload(1), 1);
+        assert_eq!(preload(u32::MAX as u64), MAX_PRELOAD);
+    }
+}
@@ -0,0 +1,353 @@
+//! Watchdogs
+//!
+//! The software watchdog resets the machine when user space stops petting
+//! it. It has [`CHANNELS`] independent countdowns: init arms channel 0 at
+//! boot and pets it from its supervision loop, so a hung PID 1 brings the
+//! system back instead of leaving it without services, and `watchdogd` pets
+//! channel 1 only while its health checks pass. Init also fires it on
+//! purpose to reboot when a critical service cannot be kept running.
+//!
+//! A hardware watchdog, if the machine has one ([`i6300esb`] or [`sbsa`]),
+//! catches the hangs the kernel cannot notice itself. Once boot is done
+//! [`start_hardware`] starts it, and the timer tick kicks it only while the
+//! scheduler shows signs of life (see [`sched_heartbeat`]), so a CPU stuck
+//! with interrupts off or a wedged scheduler lock resets the machine within
+//! [`HW_TIMEOUT_MS`].
+//!
+//! Both are driven from every timer tick, so [`check`] uses only atomics
+//! and writes straight to the serial port. Only the x86_64 APIC timer and
+//! [`crate::sched::timer_tick`] call it; where neither runs an armed
+//! watchdog never expires by itself, and the hardware one is left stopped.
+//! `watchdog=off` on the kernel command line keeps both from being armed.
+
+pub mod i6300esb;
+pub mod sbsa;
+
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+use spin::Mutex;
+
+use crate::error::KernelError;
+
+/// Shortest accepted timeout.
+pub const MIN_TIMEOUT_MS: u64 = 1000;
+
+/// Longest accepted timeout.
+pub const MAX_TIMEOUT_MS: u64 = 60 * 60 * 1000;
+
+/// Number of software watchdog channels.
+pub const CHANNELS: usize = 4;
+
+/// Hardware watchdog timeout.
+pub const HW_TIMEOUT_MS: u64 = 30_000;
+
+/// The scheduler counts as stalled after this long without a heartbeat.
+const SCHED_STALL_MS: u64 = 10_000;
+
+/// Shortest time between two hardware kicks.
+const KICK_INTERVAL_MS: u64 = 1000;
+
+/// Timeout of each channel while armed, 0 while disarmed.
+static TIMEOUT_MS: [AtomicU64; CHANNELS] = [const { AtomicU64::new(0) }; CHANNELS];
+
+/// When each channel was last armed or pet.
+static LAST_PET_MS: [AtomicU64; CHANNELS] = [const { AtomicU64::new(0) }; CHANNELS];
+
+/// Set once the reset is under way, so it is only attempted once.
+static FIRED: AtomicBool = AtomicBool::new(false);
+
+/// Scheduler ticks and scheduling passes, see [`sched_heartbeat`].
+static SCHED_BEATS: AtomicU64 = AtomicU64::new(0);
+
+/// `SCHED_BEATS` as last seen by [`check`], and when it last moved.
+static SEEN_BEATS: AtomicU64 = AtomicU64::new(0);
+static LAST_BEAT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// When the hardware watchdog was last kicked.
+static LAST_KICK_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Which `kick` the timer tick calls (`HW_*`), and its argument.
+static HW_RUNNING: AtomicU8 = AtomicU8::new(HW_NONE);
+static HW_KICK_BASE: AtomicUsize = AtomicUsize::new(0);
+
+const HW_NONE: u8 = 0;
+const HW_I6300ESB: u8 = 1;
+const HW_SBSA: u8 = 2;
+
+/// Hardware watchdog found by [`init`].
+#[derive(Debug, Clone, Copy)]
+enum Hardware {
+    I6300esb(i6300esb::Esb),
+    Sbsa(sbsa::Gwdt),
+}
+
+impl Hardware {
+    fn name(&self) -> &'static str {
+        match self {
+            Hardware::I6300esb(_) => "i6300esb",
+            Hardware::Sbsa(_) => "sbsa-gwdt",
+        }
+    }
+}
+
+static HARDWARE: Mutex<Option<Hardware>> = Mutex::new(None);
+
+fn now_ms() -> u64 {
+    crate::arch::timer::get_timestamp_ms()
+}
+
+fn disabled() -> bool {
+    crate::utils::cmdline::param("watchdog").as_deref() == Some("off")
+}
+
+/// Whether a watchdog pet at `last_pet` with `timeout` has run out at
+/// `now` (all in ms; a zero timeout is disarmed).
+fn expired(now: u64, last_pet: u64, timeout: u64) -> bool {
+    timeout != 0 && now.saturating_sub(last_pet) >= timeout
+}
+
+/// Whether the hardware watchdog should be kicked at `now`, given the last
+/// scheduler heartbeat and the last kick (all in ms).
+fn kick_due(now: u64, last_beat: u64, last_kick: u64) -> bool {
+    now.saturating_sub(last_beat) < SCHED_STALL_MS
+        && now.saturating_sub(last_kick) >= KICK_INTERVAL_MS
+}
+
+fn channel_index(channel: usize) -> Result<usize, KernelError> {
+    if channel < CHANNELS {
+        Ok(channel)
+    } else {
+        Err(KernelError::InvalidArgument {
+            name: "watchdog channel",
+            value: "out of range",
+        })
+    }
+}
+
+/// Arm (or re-arm) `channel` with `timeout_ms`, counting from now.
+pub fn arm(channel: usize, timeout_ms: u64) -> Result<(), KernelError> {
+    let channel = channel_index(channel)?;
+    if !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&timeout_ms) {
+        return Err(KernelError::InvalidArgument {
+            name: "watchdog timeout",
+            value: "out of range",
+        });
+    }
+    if disabled() {
+        return Err(KernelError::OperationNotSupported {
+            operation: "watchdog disabled by watchdog=off",
+        });
+    }
+    LAST_PET_MS[channel].store(now_ms(), Ordering::Relaxed);
+    if TIMEOUT_MS[channel].swap(timeout_ms, Ordering::Relaxed) == 0 {
+        crate::println!(
+            "[WATCHDOG] Channel {} armed with a {} ms timeout",
+            channel,
+            timeout_ms
+        );
+    }
+    Ok(())
+}
+
+/// Restart the countdown of `channel`.
+pub fn pet(channel: usize) -> Result<(), KernelError> {
+    LAST_PET_MS[channel_index(channel)?].store(now_ms(), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop the countdown of `channel`.
+pub fn disarm(channel: usize) -> Result<(), KernelError> {
+    let channel = channel_index(channel)?;
+    if TIMEOUT_MS[channel].swap(0, Ordering::Relaxed) != 0 {
+        crate::println!("[WATCHDOG] Channel {} disarmed", channel);
+    }
+    Ok(())
+}
+
+/// Milliseconds left before `channel` fires, or `None` while disarmed.
+pub fn remaining_ms(channel: usize) -> Result<Option<u64>, KernelError> {
+    let channel = channel_index(channel)?;
+    let timeout = TIMEOUT_MS[channel].load(Ordering::Relaxed);
+    if timeout == 0 {
+        return Ok(None);
+    }
+    let elapsed = now_ms().saturating_sub(LAST_PET_MS[channel].load(Ordering::Relaxed));
+    Ok(Some(timeout.saturating_sub(elapsed)))
+}
+
+/// Record that the scheduler ran. Called from every scheduler tick and
+/// scheduling pass; the hardware watchdog is only kicked while these keep
+/// coming.
+pub fn sched_heartbeat() {
+    SCHED_BEATS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Whether [`check`] runs from a timer tick that also drives the
+/// scheduler, i.e. whether anything will kick a started hardware watchdog.
+fn ticking() -> bool {
+    SEEN_BEATS.load(Ordering::Relaxed) != 0
+}
+
+/// Reset the machine if a channel has expired, and kick the hardware
+/// watchdog while the scheduler is live. Called from the timer interrupt.
+pub fn check() {
+    let now = now_ms();
+    let beats = SCHED_BEATS.load(Ordering::Relaxed);
+    if SEEN_BEATS.swap(beats, Ordering::Relaxed) != beats {
+        LAST_BEAT_MS.store(now, Ordering::Relaxed);
+    }
+
+    for channel in 0..CHANNELS {
+        let timeout = TIMEOUT_MS[channel].load(Ordering::Relaxed);
+        if expired(now, LAST_PET_MS[channel].load(Ordering::Relaxed), timeout) {
+            expire(channel);
+            return;
+        }
+    }
+
+    let kind = HW_RUNNING.load(Ordering::Acquire);
+    if kind == HW_NONE
+        || !kick_due(
+            now,
+            LAST_BEAT_MS.load(Ordering::Relaxed),
+            LAST_KICK_MS.load(Ordering::Relaxed),
+        )
+    {
+        return;
+    }
+    LAST_KICK_MS.store(now, Ordering::Relaxed);
+    let base = HW_KICK_BASE.load(Ordering::Relaxed);
+    match kind {
+        HW_I6300ESB => i6300esb::kick(base),
+        HW_SBSA => sbsa::kick(base),
+        _ => {}
+    }
+}
+
+/// Reset the machine because `channel` was not pet in time.
+fn expire(channel: usize) {
+    if FIRED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        let mut message = *b"\n[WATCHDOG] Channel 0 not pet in time, resetting\n";
+        message[20] = b'0' + channel as u8;
+        // SAFETY: COM1 is the kernel console; writing it without the serial
+        // lock may interleave with an interrupted line, which is acceptable
+        // right before a reset.
+        unsafe {
+            crate::arch::x86_64::idt::raw_serial_str(&message);
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = channel;
+    crate::power::system::reset();
+}
+
+/// Reset the machine now, on behalf of user space.
+pub fn fire() {
+    if FIRED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    crate::println!("[WATCHDOG] Fired by user space, resetting");
+    crate::power::system::reset();
+}
+
+/// Look for a hardware watchdog. It stays stopped until
+/// [`start_hardware`].
+pub fn init() {
+    let hardware = if let Some(esb) = i6300esb::probe() {
+        if esb.caused_reset() {
+            crate::println!("[WATCHDOG] The previous boot was reset by the i6300esb watchdog");
+        }
+        Hardware::I6300esb(esb)
+    } else if let Some(gwdt) = sbsa::probe() {
+        Hardware::Sbsa(gwdt)
+    } else {
+        return;
+    };
+    crate::println!("[WATCHDOG] Found {} hardware watchdog", hardware.name());
+    *HARDWARE.lock() = Some(hardware);
+}
+
+/// Start the hardware watchdog found by [`init`], if the timer tick is
+/// running to kick it. Called once boot is done, and again after resume.
+pub fn start_hardware() {
+    let Some(hardware) = *HARDWARE.lock() else {
+        return;
+    };
+    if disabled() || HW_RUNNING.load(Ordering::Acquire) != HW_NONE {
+        return;
+    }
+    if !ticking() {
+        crate::println!(
+            "[WATCHDOG] No scheduler tick to kick {}, leaving it stopped",
+            hardware.name()
+        );
+        return;
+    }
+
+    let (kind, base) = match hardware {
+        Hardware::I6300esb(esb) => {
+            esb.start(HW_TIMEOUT_MS);
+            (HW_I6300ESB, esb.kick_base())
+        }
+        Hardware::Sbsa(gwdt) => {
+            gwdt.start(HW_TIMEOUT_MS);
+            (HW_SBSA, gwdt.kick_base())
+        }
+    };
+    LAST_KICK_MS.store(now_ms(), Ordering::Relaxed);
+    HW_KICK_BASE.store(base, Ordering::Relaxed);
+    HW_RUNNING.store(kind, Ordering::Release);
+    crate::println!(
+        "[WATCHDOG] {} started with a {} ms timeout",
+        hardware.name(),
+        HW_TIMEOUT_MS
+    );
+}
+
+/// Stop the hardware watchdog, e.g. before suspending.
+pub fn stop_hardware() {
+    let Some(hardware) = *HARDWARE.lock() else {
+        return;
+    };
+    if HW_RUNNING.swap(HW_NONE, Ordering::AcqRel) == HW_NONE {
+        return;
+    }
+    match hardware {
+        Hardware::I6300esb(esb) => esb.stop(),
+        Hardware::Sbsa(gwdt) => gwdt.stop(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired() {
+        assert!(!expired(5000, 1000, 0));
+        assert!(!expired(5999, 1000, 5000));
+        assert!(expired(6000, 1000, 5000));
+        // A pet stamped after the tick's clock read is not an expiry
+        assert!(!expired(1000, 1200, 5000));
+    }
+
+    #[test]
+    fn test_kick_due() {
+        assert!(kick_due(20_000, 19_000, 18_000));
+        // Rate limited
+        assert!(!kick_due(20_000, 19_000, 19_500));
+        // Scheduler stalled
+        assert!(!kick_due(30_000, 20_000, 20_000));
+    }
+
+    #[test]
+    fn test_channel_range() {
+        assert!(pet(CHANNELS - 1).is_ok());
+        assert!(pet(CHANNELS).is_err());
+        assert_eq!(remaining_ms(CHANNELS - 1).unwrap(), None);
+    }
+}
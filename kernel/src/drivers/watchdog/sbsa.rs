@@ -0,0 +1,108 @@
+//! Arm SBSA generic watchdog
+//!
+//! The watchdog of SBSA-compliant AArch64 machines, found through the
+//! device tree (`arm,sbsa-gwdt`: control frame, then refresh frame). It
+//! counts at the system counter frequency. The first expiry (WS0) would
+//! raise an interrupt, which is not wired up; the second (WS1) resets the
+//! machine, so the offset register holds half the timeout. Any write to the
+//! refresh frame restarts the countdown.
+
+/// Control frame: status and enable
+const WCS: usize = 0x000;
+/// Control frame: offset (timeout per stage, in counter ticks)
+const WOR: usize = 0x008;
+/// Refresh frame: refresh register
+const WRR: usize = 0x000;
+
+const WCS_EN: u32 = 1 << 0;
+
+/// A probed SBSA generic watchdog.
+#[derive(Debug, Clone, Copy)]
+pub struct Gwdt {
+    /// Virtual address of the control frame.
+    control: usize,
+    /// Virtual address of the refresh frame.
+    refresh: usize,
+    /// System counter frequency in Hz.
+    frequency: u64,
+}
+
+/// Find the watchdog in the device tree.
+pub fn probe() -> Option<Gwdt> {
+    let (_, regs) = crate::drivers::dt::find_compatible("arm,sbsa-gwdt")
+        .into_iter()
+        .next()?;
+    let (&(control, _), &(refresh, _)) = (regs.first()?, regs.get(1)?);
+    Some(Gwdt {
+        control: crate::mm::phys_to_virt_addr(control) as usize,
+        refresh: crate::mm::phys_to_virt_addr(refresh) as usize,
+        frequency: counter_frequency()?,
+    })
+}
+
+/// Frequency of the system counter the watchdog runs on.
+fn counter_frequency() -> Option<u64> {
+    #[cfg(target_arch = "aarch64")]
+    {
+        let frequency: u64;
+        // SAFETY: CNTFRQ_EL0 is readable at EL1 and reading it has no side
+        // effects.
+        unsafe { core::arch::asm!("mrs {}, CNTFRQ_EL0", out(reg) frequency) };
+        (frequency != 0).then_some(frequency)
+    }
+    #[cfg(not(target_arch = "aarch64"))]
+    None
+}
+
+/// Offset register value for `timeout_ms`: half of it, since the reset
+/// comes at the second expiry.
+fn offset(timeout_ms: u64, frequency: u64) -> u32 {
+    (frequency.saturating_mul(timeout_ms) / 2000).clamp(1, u32::MAX as u64) as u32
+}
+
+/// Restart the countdown. Only touches MMIO, so it is safe from the timer
+/// interrupt.
+pub fn kick(refresh: usize) {
+    // SAFETY: `refresh` is the mapped refresh frame; writing WRR only
+    // restarts the countdown.
+    unsafe { core::ptr::write_volatile((refresh + WRR) as *mut u32, 0) };
+}
+
+impl Gwdt {
+    /// Address [`kick`] takes.
+    pub fn kick_base(&self) -> usize {
+        self.refresh
+    }
+
+    fn write_control(&self, offset: usize, value: u32) {
+        // SAFETY: `control` is the mapped control frame, which holds the
+        // 32-bit WCS and WOR registers.
+        unsafe { core::ptr::write_volatile((self.control + offset) as *mut u32, value) };
+    }
+
+    /// Program `timeout_ms` and start counting down.
+    pub fn start(&self, timeout_ms: u64) {
+        self.write_control(WCS, 0);
+        self.write_control(WOR, offset(timeout_ms, self.frequency));
+        kick(self.refresh);
+        self.write_control(WCS, WCS_EN);
+    }
+
+    /// Stop the countdown.
+    pub fn stop(&self) {
+        self.write_control(WCS, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset() {
+        // 62.5 MHz counter (QEMU): 30 s is two 15 s stages
+        assert_eq!(offset(30_000, 62_500_000), 937_500_000);
+        assert_eq!(offset(0, 62_500_000), 1);
+        assert_eq!(offset(3_600_000, 62_500_000), u32::MAX);
+    }
+}
@@ -41,6 +41,11 @@ pub enum AuxType {
     AtRandom = 25,
     /// Filename of the program being run.
     AtExecfn = 31,
+    /// Base of the VeridianOS vDSO (see [`crate::process::vdso`]).
+    ///
+    /// Not `AT_SYSINFO_EHDR`: the vDSO is not an ELF image, and Linux libcs
+    /// parse whatever that entry points at.
+    AtVdso = 0x1000,
 }
 
 /// A single entry in the auxiliary vector passed to the dynamic linker.
@@ -134,6 +139,37 @@ pub fn find_interpreter(
 // Auxiliary Vector Construction
 // ---------------------------------------------------------------------------
 
+/// Link-time address of the program headers, for AT_PHDR.
+///
+/// Uses `PT_PHDR` when present, otherwise the `LOAD` segment that maps the
+/// header table from the file. Returns 0 if the headers are not mapped.
+pub fn phdr_vaddr(data: &[u8], elf_binary: &ElfBinary) -> u64 {
+    use super::types::SegmentType;
+
+    if let Some(phdr) = elf_binary
+        .segments
+        .iter()
+        .find(|s| s.segment_type == SegmentType::Phdr)
+    {
+        return phdr.virtual_addr;
+    }
+
+    // e_phoff lives at byte 32 of the ELF64 header
+    let phoff = match data.get(32..40) {
+        Some(bytes) => u64::from_le_bytes(bytes.try_into().unwrap_or([0; 8])),
+        None => return 0,
+    };
+    elf_binary
+        .segments
+        .iter()
+        .find(|s| {
+            s.segment_type == SegmentType::Load
+                && phoff >= s.file_offset
+                && phoff < s.file_offset + s.file_size
+        })
+        .map_or(0, |s| s.virtual_addr + (phoff - s.file_offset))
+}
+
 /// Build an auxiliary vector for the dynamic linker.
 ///
 /// The auxiliary vector is placed on the user stack before the process starts.
@@ -153,18 +189,8 @@ pub fn build_aux_vector(
     random_addr: u64,
     execfn_addr: u64,
 ) -> Vec<AuxVecEntry> {
-    let phdr_count = elf_binary
-        .segments
-        .iter()
-        .filter(|s| s.segment_type == super::types::SegmentType::Phdr)
-        .count();
-
-    // If no PHDR segment, count all segments as accessible via phdr_addr.
-    let phnum = if phdr_count > 0 {
-        phdr_count as u64
-    } else {
-        elf_binary.segments.len() as u64
-    };
+    // Every program header is a segment, so AT_PHNUM is the full count.
+    let phnum = elf_binary.segments.len() as u64;
 
     let phent_size = core::mem::size_of::<Elf64ProgramHeader>() as u64;
 
@@ -211,12 +237,12 @@ pub fn build_aux_vector(
 /// # Arguments
 /// * `_elf_data`   - Raw bytes of the ELF file (reserved for future use).
 /// * `elf_binary`  - Pre-parsed ELF binary metadata.
-/// * `load_base`   - Base address where the main binary was loaded.
+/// * `phdr_addr`   - Address of the main binary's program headers in memory.
 #[cfg(feature = "alloc")]
 pub fn prepare_dynamic_linking(
     _elf_data: &[u8],
     elf_binary: &ElfBinary,
-    load_base: u64,
+    phdr_addr: u64,
 ) -> Result<Option<DynamicLinkerInfo>, KernelError> {
     // Check whether the binary has an interpreter.
     let interp_path = match &elf_binary.interpreter {
@@ -234,11 +260,6 @@ pub fn prepare_dynamic_linking(
     let interp_base = interp_elf.load_base;
     let interp_entry = interp_elf.entry_point;
 
-    // The program headers are located at load_base + phoff. For a standard
-    // ELF the PHDR segment points to them. Use load_base as the phdr address
-    // for now; the kernel stack setup will place them correctly.
-    let phdr_addr = load_base;
-
     // Build auxiliary vector. AT_RANDOM and AT_EXECFN addresses will be set
     // up later when the user stack is constructed.
     let aux_vector = build_aux_vector(elf_binary, phdr_addr, interp_base, 0, 0);
@@ -256,6 +277,7 @@ mod tests {
     use alloc::vec;
 
     use super::*;
+    use crate::elf::types::{ElfSegment, SegmentType};
 
     #[test]
     fn test_aux_type_values() {
@@ -268,6 +290,7 @@ mod tests {
         assert_eq!(AuxType::AtEntry as u64, 9);
         assert_eq!(AuxType::AtRandom as u64, 25);
         assert_eq!(AuxType::AtExecfn as u64, 31);
+        assert_eq!(AuxType::AtVdso as u64, 0x1000);
     }
 
     #[test]
@@ -341,6 +364,8 @@ mod tests {
             segments: vec![],
             interpreter: None,
             dynamic: false,
+            position_independent: false,
+            wx_needed: false,
         };
 
         let aux = build_aux_vector(&binary, 0x400040, 0, 0, 0);
@@ -362,6 +387,8 @@ mod tests {
             segments: vec![],
             interpreter: Some(String::from("/lib/ld.so.1")),
             dynamic: true,
+            position_independent: false,
+            wx_needed: false,
         };
 
         let aux = build_aux_vector(&binary, 0x400040, 0x7F000000, 0xBEEF, 0xCAFE);
@@ -377,4 +404,70 @@ mod tests {
         assert!(has(AuxType::AtExecfn));
         assert!(has(AuxType::AtNull));
     }
+
+    fn segment(segment_type: SegmentType, vaddr: u64, offset: u64, size: u64) -> ElfSegment {
+        ElfSegment {
+            segment_type,
+            virtual_addr: vaddr,
+            physical_addr: vaddr,
+            file_offset: offset,
+            file_size: size,
+            memory_size: size,
+            flags: 0,
+            alignment: 0x1000,
+        }
+    }
+
+    fn binary_with(segments: Vec<ElfSegment>) -> ElfBinary {
+        ElfBinary {
+            entry_point: 0x401000,
+            load_base: 0x400000,
+            load_size: 0x2000,
+            segments,
+            interpreter: None,
+            dynamic: false,
+            position_independent: false,
+            wx_needed: false,
+        }
+    }
+
+    fn header_with_phoff(phoff: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 64];
+        data[32..40].copy_from_slice(&phoff.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_phdr_vaddr_prefers_pt_phdr() {
+        let binary = binary_with(vec![
+            segment(SegmentType::Phdr, 0x400040, 0x40, 0x70),
+            segment(SegmentType::Load, 0x400000, 0, 0x1000),
+        ]);
+        assert_eq!(phdr_vaddr(&header_with_phoff(0x40), &binary), 0x400040);
+    }
+
+    #[test]
+    fn test_phdr_vaddr_from_load_segment() {
+        let binary = binary_with(vec![
+            segment(SegmentType::Load, 0x400000, 0, 0x1000),
+            segment(SegmentType::Load, 0x601000, 0x1000, 0x200),
+        ]);
+        assert_eq!(phdr_vaddr(&header_with_phoff(0x40), &binary), 0x400040);
+        assert_eq!(phdr_vaddr(&header_with_phoff(0x1010), &binary), 0x601010);
+        // Headers outside every LOAD segment are not mapped
+        assert_eq!(phdr_vaddr(&header_with_phoff(0x5000), &binary), 0);
+        assert_eq!(phdr_vaddr(&[], &binary), 0);
+    }
+
+    #[test]
+    fn test_build_aux_vector_phnum_counts_all_headers() {
+        let binary = binary_with(vec![
+            segment(SegmentType::Phdr, 0x400040, 0x40, 0xa8),
+            segment(SegmentType::Load, 0x400000, 0, 0x1000),
+            segment(SegmentType::Tls, 0x400800, 0x800, 0x10),
+        ]);
+        let aux = build_aux_vector(&binary, 0x400040, 0, 0, 0);
+        let phnum = aux.iter().find(|e| e.type_id == AuxType::AtPhnum).unwrap();
+        assert_eq!(phnum.value, 3);
+    }
 }
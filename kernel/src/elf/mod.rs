@@ -7,6 +7,7 @@
 #![allow(clippy::slow_vector_initialization, clippy::unnecessary_cast)]
 
 pub mod dynamic;
+pub mod symbols;
 pub mod types;
 
 // Re-export all types for backward compatibility
@@ -141,10 +142,26 @@ impl ElfLoader {
         Self
     }
 
-    /// Load an ELF binary directly into a VAS
+    /// Load an ELF binary directly into a VAS at its link address
     pub fn load(
         data: &[u8],
         vas: &mut crate::mm::vas::VirtualAddressSpace,
+    ) -> Result<u64, crate::error::KernelError> {
+        Self::load_at(data, vas, 0)
+    }
+
+    /// Load an ELF binary into a VAS with every segment displaced by `bias`.
+    ///
+    /// A non-zero bias is only meaningful for position-independent binaries
+    /// (see [`Self::pie_bias`]); their relative relocations are applied here
+    /// unless an interpreter will do it. Returns the (biased) entry point.
+    ///
+    /// Segments that are both writable and executable are refused unless the
+    /// binary is marked `PT_WXNEEDED`.
+    pub fn load_at(
+        data: &[u8],
+        vas: &mut crate::mm::vas::VirtualAddressSpace,
+        bias: u64,
     ) -> Result<u64, crate::error::KernelError> {
         let loader = Self::new();
         let binary =
@@ -155,13 +172,35 @@ impl ElfLoader {
                     value: "failed to parse ELF binary",
                 })?;
 
+        // Enforce W^X before mapping anything
+        for segment in &binary.segments {
+            if segment.segment_type != SegmentType::Load {
+                continue;
+            }
+            let writable = segment.flags & 0x2 != 0; // PF_W
+            let executable = segment.flags & 0x1 != 0; // PF_X
+            if !crate::security::memory_protection::user_mapping_allowed(
+                writable,
+                executable,
+                binary.wx_needed,
+            ) {
+                return Err(crate::error::KernelError::PermissionDenied {
+                    operation: "map writable and executable ELF segment",
+                });
+            }
+        }
+
+        let entry_point = binary.entry_point.wrapping_add(bias);
+
         // Process each LOAD segment
         let mut _load_idx = 0u32;
         for segment in &binary.segments {
             if segment.segment_type == SegmentType::Load {
+                let segment_addr = segment.virtual_addr.wrapping_add(bias);
+
                 // Calculate page-aligned addresses
-                let page_start = segment.virtual_addr & !0xFFF;
-                let page_end = (segment.virtual_addr + segment.memory_size + 0xFFF) & !0xFFF;
+                let page_start = segment_addr & !0xFFF;
+                let page_end = (segment_addr + segment.memory_size + 0xFFF) & !0xFFF;
                 let num_pages = ((page_end - page_start) / 0x1000) as usize;
 
                 // Diagnostic: log each LOAD segment for multi-LOAD debugging
@@ -171,7 +210,7 @@ impl ElfLoader {
                     crate::arch::x86_64::idt::raw_serial_str(b"[ELF] LOAD#");
                     crate::arch::x86_64::idt::raw_serial_hex(_load_idx as u64);
                     crate::arch::x86_64::idt::raw_serial_str(b" va=0x");
-                    crate::arch::x86_64::idt::raw_serial_hex(segment.virtual_addr);
+                    crate::arch::x86_64::idt::raw_serial_hex(segment_addr);
                     crate::arch::x86_64::idt::raw_serial_str(b" memsz=0x");
                     crate::arch::x86_64::idt::raw_serial_hex(segment.memory_size);
                     crate::arch::x86_64::idt::raw_serial_str(b" pages=0x");
@@ -182,19 +221,33 @@ impl ElfLoader {
                 }
                 _load_idx += 1;
 
-                // Map pages for this segment
-                for i in 0..num_pages {
+                // Determine page flags based on segment flags
+                let mut flags = crate::mm::PageFlags::USER | crate::mm::PageFlags::PRESENT;
+                if (segment.flags & 0x2) != 0 {
+                    // PF_W
+                    flags |= crate::mm::PageFlags::WRITABLE;
+                }
+                if (segment.flags & 0x1) == 0 {
+                    // PF_X
+                    flags |= crate::mm::PageFlags::NO_EXECUTE;
+                }
+
+                // Map pages for this segment. Whole 2MB stretches of a large
+                // segment get a single 2MB page; a boundary page shared with
+                // the previous segment, or a lack of aligned physical memory,
+                // falls back to 4KB pages.
+                const LARGE_PAGE_SIZE: u64 = crate::mm::PageSize::Large as u64;
+                const LARGE_PAGE_PAGES: usize = (LARGE_PAGE_SIZE / 0x1000) as usize;
+                let mut i = 0;
+                while i < num_pages {
                     let addr = page_start + (i as u64 * 0x1000);
 
-                    // Determine page flags based on segment flags
-                    let mut flags = crate::mm::PageFlags::USER | crate::mm::PageFlags::PRESENT;
-                    if (segment.flags & 0x2) != 0 {
-                        // PF_W
-                        flags |= crate::mm::PageFlags::WRITABLE;
-                    }
-                    if (segment.flags & 0x1) == 0 {
-                        // PF_X
-                        flags |= crate::mm::PageFlags::NO_EXECUTE;
+                    if addr.is_multiple_of(LARGE_PAGE_SIZE)
+                        && num_pages - i >= LARGE_PAGE_PAGES
+                        && vas.map_huge_page(addr as usize, flags).is_ok()
+                    {
+                        i += LARGE_PAGE_PAGES;
+                        continue;
                     }
 
                     vas.map_page(addr as usize, flags)?;
@@ -215,6 +268,8 @@ impl ElfLoader {
                             })?;
                         }
                     }
+
+                    i += 1;
                 }
 
                 // Copy segment data from file via physical memory window.
@@ -225,17 +280,23 @@ impl ElfLoader {
                 if segment.file_size > 0 {
                     let src_slice = &data[segment.file_offset as usize
                         ..(segment.file_offset + segment.file_size) as usize];
-                    write_to_user_pages(vas, segment.virtual_addr, src_slice)?;
+                    write_to_user_pages(vas, segment_addr, src_slice)?;
                 }
 
                 // Zero BSS portion if memory size > file size
                 if segment.memory_size > segment.file_size {
                     let bss_size = (segment.memory_size - segment.file_size) as usize;
-                    zero_user_pages(vas, segment.virtual_addr + segment.file_size, bss_size)?;
+                    zero_user_pages(vas, segment_addr + segment.file_size, bss_size)?;
                 }
             }
         }
 
+        // A relocated image without an interpreter (static PIE) has nobody
+        // else to fix up its absolute pointers
+        if bias != 0 && binary.interpreter.is_none() {
+            loader.relocate_user_image(data, vas, bias)?;
+        }
+
         // Sanity-check that the entry point is mapped and executable
         #[cfg(feature = "alloc")]
         {
@@ -251,10 +312,10 @@ impl ElfLoader {
             let mapper = unsafe { crate::mm::vas::create_mapper_from_root_pub(pt_root) };
             use crate::mm::VirtualAddress;
 
-            let entry_page = VirtualAddress(binary.entry_point & !0xFFF);
+            let entry_page = VirtualAddress(entry_point & !0xFFF);
             let (_, flags) = mapper.translate_page(entry_page).map_err(|_| {
                 crate::error::KernelError::UnmappedMemory {
-                    addr: binary.entry_point as usize,
+                    addr: entry_point as usize,
                 }
             })?;
 
@@ -273,11 +334,29 @@ impl ElfLoader {
         // SAFETY: raw_serial_str writes to the COM1 I/O port for diagnostic output.
         unsafe {
             crate::arch::x86_64::idt::raw_serial_str(b"[ELF] entry=0x");
-            crate::arch::x86_64::idt::raw_serial_hex(binary.entry_point);
+            crate::arch::x86_64::idt::raw_serial_hex(entry_point);
             crate::arch::x86_64::idt::raw_serial_str(b"\n");
         }
 
-        Ok(binary.entry_point)
+        Ok(entry_point)
+    }
+
+    /// Load bias that places a position-independent binary at `pie_base`.
+    ///
+    /// Fixed-address executables, and PIEs already linked at or above
+    /// `pie_base`, get a bias of 0. The base is rounded down to the largest
+    /// LOAD segment alignment so segment offsets within a page are preserved.
+    pub fn pie_bias(binary: &ElfBinary, pie_base: u64) -> u64 {
+        if !binary.position_independent || binary.load_base >= pie_base {
+            return 0;
+        }
+        let align = binary
+            .segments
+            .iter()
+            .filter(|s| s.segment_type == SegmentType::Load && s.alignment.is_power_of_two())
+            .map(|s| s.alignment)
+            .fold(0x1000, u64::max);
+        (pie_base & !(align - 1)) - (binary.load_base & !(align - 1))
     }
 
     /// Parse an ELF binary from a byte slice
@@ -302,6 +381,10 @@ impl ElfLoader {
             .iter()
             .any(|ph| ph.p_type == ProgramType::Dynamic as u32);
 
+        let wx_needed = program_headers
+            .iter()
+            .any(|ph| ph.p_type == ProgramType::WxNeeded as u32);
+
         // Convert program headers to segments
         let mut segments = Vec::new();
         for ph in &program_headers {
@@ -336,6 +419,8 @@ impl ElfLoader {
             segments,
             interpreter,
             dynamic,
+            position_independent: header.elf_type == ElfType::SharedObject as u16,
+            wx_needed,
         })
     }
 
@@ -875,6 +960,63 @@ impl ElfLoader {
         self.perform_relocations_arch(base_addr, &relocations, &symbols, header.machine)
     }
 
+    /// Apply the relative relocations of an image loaded `bias` bytes above
+    /// its link address in a user VAS.
+    ///
+    /// Only `*_RELATIVE` entries are handled; they are all a static PIE
+    /// carries. The image is written through the process's page tables, so
+    /// read-only (RELRO) pages can be patched too.
+    fn relocate_user_image(
+        &self,
+        data: &[u8],
+        vas: &crate::mm::VirtualAddressSpace,
+        bias: u64,
+    ) -> Result<(), crate::error::KernelError> {
+        let malformed = |_| crate::error::KernelError::InvalidArgument {
+            name: "elf_data",
+            value: "malformed relocation tables",
+        };
+        let header = self.parse_header(data).map_err(malformed)?;
+        let program_headers = self
+            .parse_program_headers(data, &header)
+            .map_err(malformed)?;
+
+        let dynamic_ph = match program_headers
+            .iter()
+            .find(|ph| ph.p_type == ProgramType::Dynamic as u32)
+        {
+            Some(ph) => ph,
+            None => return Ok(()),
+        };
+        let dyn_info = self
+            .parse_dynamic_section(data, dynamic_ph.p_offset, dynamic_ph.p_filesz)
+            .map_err(malformed)?;
+
+        let relative_type = match header.machine {
+            62 => 8,     // R_X86_64_RELATIVE
+            183 => 1027, // R_AARCH64_RELATIVE
+            243 => 3,    // R_RISCV_RELATIVE
+            _ => return Ok(()),
+        };
+
+        let mut relocations = Vec::new();
+        if let Some(rela_vaddr) = dyn_info.rela {
+            if dyn_info.relasz > 0 && dyn_info.relaent > 0 {
+                if let Some(rela_off) = self.vaddr_to_file_offset(&program_headers, rela_vaddr) {
+                    let count = dyn_info.relasz / dyn_info.relaent;
+                    self.parse_rela_entries(data, rela_off as usize, count, &mut relocations)
+                        .map_err(malformed)?;
+                }
+            }
+        }
+
+        for reloc in relocations.iter().filter(|r| r.reloc_type == relative_type) {
+            let value = bias.wrapping_add(reloc.addend as u64);
+            write_to_user_pages(vas, bias.wrapping_add(reloc.offset), &value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
     /// Convert a virtual address to a file offset using LOAD segment mappings.
     fn vaddr_to_file_offset(
         &self,
@@ -1366,6 +1508,47 @@ mod tests {
 
         let result = loader.parse(&data);
         assert!(result.is_ok());
+        assert!(result.unwrap().position_independent);
+    }
+
+    #[test]
+    fn test_pie_bias() {
+        let loader = ElfLoader::new();
+
+        let pie = loader
+            .parse(&make_minimal_elf(3, 62, 0x1040, 0x0, 0x2000))
+            .unwrap();
+        assert_eq!(
+            ElfLoader::pie_bias(&pie, 0x5555_5556_7000),
+            0x5555_5556_7000
+        );
+        // Rounded down to the segment alignment
+        assert_eq!(
+            ElfLoader::pie_bias(&pie, 0x5555_5556_7abc),
+            0x5555_5556_7000
+        );
+
+        // Fixed-address executables are never moved
+        let exec = loader
+            .parse(&make_minimal_elf(2, 62, 0x401000, 0x400000, 0x1000))
+            .unwrap();
+        assert!(!exec.position_independent);
+        assert_eq!(ElfLoader::pie_bias(&exec, 0x5555_5556_7000), 0);
+    }
+
+    #[test]
+    fn test_parse_wx_needed() {
+        let loader = ElfLoader::new();
+        let mut data = make_minimal_elf(2, 62, 0x401000, 0x400000, 0x1000);
+        let binary = loader.parse(&data).unwrap();
+        assert!(!binary.wx_needed);
+
+        // Append a PT_WXNEEDED program header
+        let mut marker = vec![0u8; core::mem::size_of::<Elf64ProgramHeader>()];
+        marker[0..4].copy_from_slice(&(ProgramType::WxNeeded as u32).to_le_bytes());
+        data.extend_from_slice(&marker);
+        data[56] = 2; // phnum
+        assert!(loader.parse(&data).unwrap().wx_needed);
     }
 
     // --- Segment parsing tests ---
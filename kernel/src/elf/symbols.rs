@@ -0,0 +1,185 @@
+//! Function symbols of user executables
+//!
+//! Exec keeps the `.symtab` function symbols of the new image so a fault in
+//! user space can print a symbolized backtrace. Stripped binaries have no
+//! `.symtab` and get raw addresses only.
+
+use alloc::vec::Vec;
+
+/// Most symbols kept per executable.
+pub const MAX_SYMBOLS: usize = 16384;
+
+/// Longest name kept.
+const MAX_NAME: usize = 255;
+
+const SHT_SYMTAB: u32 = 2;
+const STT_FUNC: u8 = 2;
+const SYM_LEN: usize = 24;
+
+struct Entry {
+    addr: u64,
+    size: u64,
+    name_off: u32,
+    name_len: u8,
+}
+
+/// Sorted function symbols of a loaded executable, at their runtime
+/// addresses.
+pub struct SymbolMap {
+    entries: Vec<Entry>,
+    names: Vec<u8>,
+}
+
+fn read_u16(buf: &[u8], off: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(off..off + 2)?.try_into().ok()?))
+}
+
+fn read_u32(buf: &[u8], off: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(off..off + 4)?.try_into().ok()?))
+}
+
+fn read_u64(buf: &[u8], off: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(off..off + 8)?.try_into().ok()?))
+}
+
+impl SymbolMap {
+    /// Collect the function symbols of ELF64 image `data`, loaded `bias`
+    /// bytes above its link address. Returns `None` if it has none.
+    pub fn from_elf(data: &[u8], bias: u64) -> Option<Self> {
+        let shoff = read_u64(data, 0x28)? as usize;
+        let shentsize = read_u16(data, 0x3A)? as usize;
+        let shnum = read_u16(data, 0x3C)? as usize;
+        let section = |index: usize| shoff.checked_add(index.checked_mul(shentsize)?);
+
+        let symtab = (0..shnum)
+            .filter_map(section)
+            .find(|&h| read_u32(data, h + 4) == Some(SHT_SYMTAB))?;
+        let sym_off = read_u64(data, symtab + 0x18)? as usize;
+        let sym_size = read_u64(data, symtab + 0x20)? as usize;
+        let strtab = section(read_u32(data, symtab + 0x28)? as usize)?;
+        let str_off = read_u64(data, strtab + 0x18)? as usize;
+        let str_size = read_u64(data, strtab + 0x20)? as usize;
+        let syms = data.get(sym_off..sym_off.checked_add(sym_size)?)?;
+        let strings = data.get(str_off..str_off.checked_add(str_size)?)?;
+
+        let mut entries = Vec::new();
+        let mut names = Vec::new();
+        for sym in syms.chunks_exact(SYM_LEN) {
+            if entries.len() == MAX_SYMBOLS {
+                break;
+            }
+            let value = read_u64(sym, 8)?;
+            if sym[4] & 0xf != STT_FUNC || value == 0 {
+                continue;
+            }
+            let start = read_u32(sym, 0)? as usize;
+            let Some(rest) = strings.get(start..) else {
+                continue;
+            };
+            let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+            let name = &rest[..len.min(MAX_NAME)];
+            entries.push(Entry {
+                addr: value.wrapping_add(bias),
+                size: read_u64(sym, 16)?,
+                name_off: names.len() as u32,
+                name_len: name.len() as u8,
+            });
+            names.extend_from_slice(name);
+        }
+        if entries.is_empty() {
+            return None;
+        }
+        entries.sort_unstable_by_key(|e| e.addr);
+        Some(Self { entries, names })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The function containing `addr` and the offset into it. A symbol
+    /// without a size is taken to extend to the next one.
+    pub fn lookup(&self, addr: u64) -> Option<(&str, u64)> {
+        let index = self.entries.partition_point(|e| e.addr <= addr);
+        let entry = &self.entries[index.checked_sub(1)?];
+        let offset = addr - entry.addr;
+        if entry.size != 0 && offset >= entry.size {
+            return None;
+        }
+        let start = entry.name_off as usize;
+        let name = &self.names[start..start + entry.name_len as usize];
+        Some((core::str::from_utf8(name).unwrap_or("?"), offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal ELF64 image: section headers for a null section, a
+    /// `.symtab` and its `.strtab`.
+    fn image(symbols: &[(&str, u8, u64, u64)]) -> Vec<u8> {
+        let mut strtab = alloc::vec![0u8];
+        let mut symtab = alloc::vec![0u8; SYM_LEN];
+        for &(name, kind, value, size) in symbols {
+            let mut sym = [0u8; SYM_LEN];
+            sym[0..4].copy_from_slice(&(strtab.len() as u32).to_le_bytes());
+            sym[4] = kind;
+            sym[8..16].copy_from_slice(&value.to_le_bytes());
+            sym[16..24].copy_from_slice(&size.to_le_bytes());
+            symtab.extend_from_slice(&sym);
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        let sym_off = 64;
+        let str_off = sym_off + symtab.len();
+        let shoff = str_off + strtab.len();
+        let mut data = alloc::vec![0u8; 64];
+        data[0x28..0x30].copy_from_slice(&(shoff as u64).to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&symtab);
+        data.extend_from_slice(&strtab);
+        data.extend_from_slice(&[0u8; 64]);
+        for (kind, off, size, link) in [
+            (SHT_SYMTAB, sym_off, symtab.len(), 2u32),
+            (3, str_off, strtab.len(), 0),
+        ] {
+            let mut sh = [0u8; 64];
+            sh[4..8].copy_from_slice(&kind.to_le_bytes());
+            sh[0x18..0x20].copy_from_slice(&(off as u64).to_le_bytes());
+            sh[0x20..0x28].copy_from_slice(&(size as u64).to_le_bytes());
+            sh[0x28..0x2C].copy_from_slice(&link.to_le_bytes());
+            data.extend_from_slice(&sh);
+        }
+        data
+    }
+
+    #[test]
+    fn test_function_symbols() {
+        let data = image(&[
+            ("main", STT_FUNC, 0x1100, 0x40),
+            ("counter", 1, 0x3000, 8),
+            ("_start", STT_FUNC, 0x1000, 0x20),
+            ("helper", STT_FUNC, 0x1200, 0),
+        ]);
+        let map = SymbolMap::from_elf(&data, 0x10000).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.lookup(0x11008), Some(("_start", 8)));
+        assert_eq!(map.lookup(0x11020), None);
+        assert_eq!(map.lookup(0x1113f), Some(("main", 0x3f)));
+        assert_eq!(map.lookup(0x11300), Some(("helper", 0x100)));
+        assert_eq!(map.lookup(0x1008), None);
+    }
+
+    #[test]
+    fn test_stripped_binary() {
+        assert!(SymbolMap::from_elf(&image(&[("counter", 1, 0x3000, 8)]), 0).is_none());
+        assert!(SymbolMap::from_elf(&[0u8; 16], 0).is_none());
+    }
+}
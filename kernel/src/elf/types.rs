@@ -85,6 +85,9 @@ pub enum ProgramType {
     Shlib = 5,
     Phdr = 6,
     Tls = 7,
+    /// Marks a binary that needs writable+executable mappings (a JIT).
+    /// Emitted by `ld -z wxneeded`; exempts the process from W^X.
+    WxNeeded = 0x65a3_dbe7,
 }
 
 /// Program header
@@ -197,6 +200,10 @@ pub struct ElfBinary {
     pub segments: Vec<ElfSegment>,
     pub interpreter: Option<String>,
     pub dynamic: bool,
+    /// ET_DYN executable that can be loaded at any base (PIE)
+    pub position_independent: bool,
+    /// Has a `PT_WXNEEDED` header (see [`ProgramType::WxNeeded`])
+    pub wx_needed: bool,
 }
 
 /// Dynamic linking information
@@ -101,11 +101,6 @@ pub enum KernelError {
     NotInitialized {
         subsystem: &'static str,
     },
-    /// Legacy string error for gradual migration from &'static str patterns.
-    /// New code should use specific error variants instead.
-    LegacyError {
-        message: &'static str,
-    },
 }
 
 /// Capability-specific errors
@@ -208,6 +203,8 @@ pub enum FsError {
     SymlinkLoop,
     /// No space left on device (ENOSPC)
     NoSpace,
+    /// Link or rename across filesystems (EXDEV)
+    CrossDevice,
 }
 
 /// Result type alias for kernel operations
@@ -281,7 +278,6 @@ impl fmt::Display for KernelError {
             Self::NotInitialized { subsystem } => {
                 write!(f, "Subsystem not initialized: {}", subsystem)
             }
-            Self::LegacyError { message } => write!(f, "{}", message),
         }
     }
 }
@@ -334,17 +330,6 @@ impl From<FsError> for KernelError {
     }
 }
 
-/// Conversion from legacy &'static str errors to KernelError.
-///
-/// This enables gradual migration: functions returning Result<T, &'static str>
-/// can be called with `?` from functions returning Result<T, KernelError>.
-/// New code should prefer specific error variants over this conversion.
-impl From<&'static str> for KernelError {
-    fn from(msg: &'static str) -> Self {
-        Self::LegacyError { message: msg }
-    }
-}
-
 // Helper macro for easy error creation
 #[macro_export]
 macro_rules! kernel_error {
@@ -451,14 +436,6 @@ mod tests {
         assert_eq!(e.to_string(), "Broken pipe");
     }
 
-    #[test]
-    fn test_display_legacy_error() {
-        let e = KernelError::LegacyError {
-            message: "old error",
-        };
-        assert_eq!(e.to_string(), "old error");
-    }
-
     #[test]
     fn test_display_timeout() {
         let e = KernelError::Timeout {
@@ -500,17 +477,6 @@ mod tests {
         assert_eq!(ke, KernelError::FsError(FsError::NotFound));
     }
 
-    #[test]
-    fn test_from_static_str() {
-        let ke: KernelError = "something broke".into();
-        assert_eq!(
-            ke,
-            KernelError::LegacyError {
-                message: "something broke"
-            }
-        );
-    }
-
     #[test]
     fn test_from_cap_error_invalid() {
         let ce = CapError::InvalidCapability;
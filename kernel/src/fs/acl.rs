@@ -0,0 +1,321 @@
+//! POSIX Access Control Lists
+//!
+//! ACLs are stored as extended attributes in the Linux binary format so that
+//! `setfacl`/`getfacl` style tools and host-built images interoperate:
+//! a little-endian `u32` version header ([`ACL_EA_VERSION`]) followed by
+//! 8-byte entries of `{ tag: u16, perm: u16, id: u32 }`.
+//!
+//! [`Acl::permits`] implements the POSIX.1e access check algorithm and is
+//! used by [`super::check_access`] when a node carries an access ACL.
+
+use alloc::vec::Vec;
+
+use crate::{
+    error::KernelError,
+    process::creds::{Credentials, ACCESS_EXEC, ACCESS_READ, ACCESS_WRITE},
+};
+
+/// Extended attribute holding the access ACL of a file.
+pub const ACL_ACCESS_XATTR: &str = "system.posix_acl_access";
+
+/// Extended attribute holding the default ACL inherited by new entries of a
+/// directory.
+pub const ACL_DEFAULT_XATTR: &str = "system.posix_acl_default";
+
+/// Version number of the binary ACL format.
+pub const ACL_EA_VERSION: u32 = 2;
+
+/// Entry tag: permissions of the file owner.
+pub const ACL_USER_OBJ: u16 = 0x01;
+/// Entry tag: permissions of a named user.
+pub const ACL_USER: u16 = 0x02;
+/// Entry tag: permissions of the owning group.
+pub const ACL_GROUP_OBJ: u16 = 0x04;
+/// Entry tag: permissions of a named group.
+pub const ACL_GROUP: u16 = 0x08;
+/// Entry tag: upper bound for named entries and the owning group.
+pub const ACL_MASK: u16 = 0x10;
+/// Entry tag: permissions of everyone else.
+pub const ACL_OTHER: u16 = 0x20;
+
+/// Qualifier stored in entries that do not name a user or group.
+pub const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+/// Size of the version header.
+const HEADER_SIZE: usize = 4;
+
+/// Size of one encoded entry.
+const ENTRY_SIZE: usize = 8;
+
+/// A single ACL entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AclEntry {
+    pub tag: u16,
+    /// `ACCESS_READ | ACCESS_WRITE | ACCESS_EXEC` bits.
+    pub perm: u16,
+    /// uid or gid for `ACL_USER` / `ACL_GROUP`, otherwise
+    /// [`ACL_UNDEFINED_ID`].
+    pub id: u32,
+}
+
+/// A validated POSIX ACL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Acl {
+    entries: Vec<AclEntry>,
+}
+
+fn invalid(value: &'static str) -> KernelError {
+    KernelError::InvalidArgument { name: "acl", value }
+}
+
+impl Acl {
+    /// Decode and validate an ACL in the binary xattr format.
+    ///
+    /// The ACL must contain exactly one owner, owning-group and other entry,
+    /// at most one mask, no duplicate named entries, and a mask whenever
+    /// named entries are present.
+    pub fn parse(bytes: &[u8]) -> Result<Self, KernelError> {
+        if bytes.len() < HEADER_SIZE || !(bytes.len() - HEADER_SIZE).is_multiple_of(ENTRY_SIZE) {
+            return Err(invalid("bad length"));
+        }
+        let version = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        if version != ACL_EA_VERSION {
+            return Err(invalid("unsupported version"));
+        }
+
+        let entries: Vec<AclEntry> = bytes[HEADER_SIZE..]
+            .chunks_exact(ENTRY_SIZE)
+            .map(|e| AclEntry {
+                tag: u16::from_le_bytes([e[0], e[1]]),
+                perm: u16::from_le_bytes([e[2], e[3]]),
+                id: u32::from_le_bytes([e[4], e[5], e[6], e[7]]),
+            })
+            .collect();
+
+        let count = |tag: u16| entries.iter().filter(|e| e.tag == tag).count();
+        if count(ACL_USER_OBJ) != 1 || count(ACL_GROUP_OBJ) != 1 || count(ACL_OTHER) != 1 {
+            return Err(invalid("missing required entry"));
+        }
+        let named = count(ACL_USER) + count(ACL_GROUP);
+        match count(ACL_MASK) {
+            0 if named > 0 => return Err(invalid("named entries require a mask")),
+            0 | 1 => {}
+            _ => return Err(invalid("duplicate mask")),
+        }
+
+        for (i, e) in entries.iter().enumerate() {
+            if e.perm & !0o7 != 0 {
+                return Err(invalid("bad permission bits"));
+            }
+            match e.tag {
+                ACL_USER | ACL_GROUP => {
+                    if entries[..i].iter().any(|p| p.tag == e.tag && p.id == e.id) {
+                        return Err(invalid("duplicate named entry"));
+                    }
+                }
+                ACL_USER_OBJ | ACL_GROUP_OBJ | ACL_MASK | ACL_OTHER => {}
+                _ => return Err(invalid("unknown tag")),
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Encode the ACL in the binary xattr format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.entries.len() * ENTRY_SIZE);
+        out.extend_from_slice(&ACL_EA_VERSION.to_le_bytes());
+        for e in &self.entries {
+            out.extend_from_slice(&e.tag.to_le_bytes());
+            out.extend_from_slice(&e.perm.to_le_bytes());
+            out.extend_from_slice(&e.id.to_le_bytes());
+        }
+        out
+    }
+
+    /// The ACL entries in stored order.
+    pub fn entries(&self) -> &[AclEntry] {
+        &self.entries
+    }
+
+    fn perm_of(&self, tag: u16) -> Option<u16> {
+        self.entries.iter().find(|e| e.tag == tag).map(|e| e.perm)
+    }
+
+    /// The permission bits (`0o777`) equivalent to this ACL.
+    ///
+    /// The group class maps to the mask entry when present, otherwise to the
+    /// owning-group entry, as required when keeping the file mode in sync.
+    pub fn mode_bits(&self) -> u32 {
+        let owner = self.perm_of(ACL_USER_OBJ).unwrap_or(0) as u32;
+        let group = self
+            .perm_of(ACL_MASK)
+            .or_else(|| self.perm_of(ACL_GROUP_OBJ))
+            .unwrap_or(0) as u32;
+        let other = self.perm_of(ACL_OTHER).unwrap_or(0) as u32;
+        (owner << 6) | (group << 3) | other
+    }
+
+    /// POSIX.1e access check for a file owned by `owner`:`group`.
+    ///
+    /// `mask` is a combination of `ACCESS_*` bits. Privileged callers are
+    /// handled by the caller; this only evaluates the entries.
+    pub fn permits(&self, creds: &Credentials, owner: u32, group: u32, mask: u32) -> bool {
+        let want = (mask & (ACCESS_READ | ACCESS_WRITE | ACCESS_EXEC)) as u16;
+        let grants = |perm: u16| perm & want == want;
+        let limit = self.perm_of(ACL_MASK).unwrap_or(0o7);
+
+        if creds.euid == owner {
+            return grants(self.perm_of(ACL_USER_OBJ).unwrap_or(0));
+        }
+        if let Some(e) = self
+            .entries
+            .iter()
+            .find(|e| e.tag == ACL_USER && e.id == creds.euid)
+        {
+            return grants(e.perm & limit);
+        }
+
+        // Group class: access is granted if any matching group entry grants
+        // it; a match that grants nothing denies without consulting "other".
+        let mut matched = false;
+        for e in &self.entries {
+            let is_match = match e.tag {
+                ACL_GROUP_OBJ => creds.in_group(group),
+                ACL_GROUP => creds.in_group(e.id),
+                _ => false,
+            };
+            if is_match {
+                if grants(e.perm & limit) {
+                    return true;
+                }
+                matched = true;
+            }
+        }
+        if matched {
+            return false;
+        }
+
+        grants(self.perm_of(ACL_OTHER).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tag: u16, perm: u16, id: u32) -> AclEntry {
+        AclEntry { tag, perm, id }
+    }
+
+    fn encode(entries: &[AclEntry]) -> Vec<u8> {
+        Acl {
+            entries: entries.to_vec(),
+        }
+        .to_bytes()
+    }
+
+    /// user::rw-, user:1001:rw-, group::r--, group:50:rwx, mask::r-x,
+    /// other::---
+    fn sample() -> Acl {
+        Acl::parse(&encode(&[
+            entry(ACL_USER_OBJ, 6, ACL_UNDEFINED_ID),
+            entry(ACL_USER, 6, 1001),
+            entry(ACL_GROUP_OBJ, 4, ACL_UNDEFINED_ID),
+            entry(ACL_GROUP, 7, 50),
+            entry(ACL_MASK, 5, ACL_UNDEFINED_ID),
+            entry(ACL_OTHER, 0, ACL_UNDEFINED_ID),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let acl = sample();
+        assert_eq!(Acl::parse(&acl.to_bytes()).unwrap(), acl);
+        assert_eq!(acl.entries().len(), 6);
+    }
+
+    #[test]
+    fn test_rejects_missing_required_entry() {
+        let bytes = encode(&[
+            entry(ACL_USER_OBJ, 6, ACL_UNDEFINED_ID),
+            entry(ACL_OTHER, 4, ACL_UNDEFINED_ID),
+        ]);
+        assert!(Acl::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_rejects_named_entry_without_mask() {
+        let bytes = encode(&[
+            entry(ACL_USER_OBJ, 6, ACL_UNDEFINED_ID),
+            entry(ACL_USER, 6, 1001),
+            entry(ACL_GROUP_OBJ, 4, ACL_UNDEFINED_ID),
+            entry(ACL_OTHER, 4, ACL_UNDEFINED_ID),
+        ]);
+        assert!(Acl::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_header() {
+        assert!(Acl::parse(&[]).is_err());
+        let mut bytes = sample().to_bytes();
+        bytes[0] = 1;
+        assert!(Acl::parse(&bytes).is_err());
+        bytes[0] = 2;
+        bytes.push(0);
+        assert!(Acl::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_mode_bits_use_mask() {
+        assert_eq!(sample().mode_bits(), 0o650);
+    }
+
+    #[test]
+    fn test_owner_uses_owner_entry() {
+        let acl = sample();
+        let owner = Credentials::new(1000, 100);
+        assert!(acl.permits(&owner, 1000, 100, ACCESS_READ | ACCESS_WRITE));
+        assert!(!acl.permits(&owner, 1000, 100, ACCESS_EXEC));
+    }
+
+    #[test]
+    fn test_named_user_is_limited_by_mask() {
+        let acl = sample();
+        let user = Credentials::new(1001, 200);
+        assert!(acl.permits(&user, 1000, 100, ACCESS_READ));
+        assert!(!acl.permits(&user, 1000, 100, ACCESS_WRITE));
+    }
+
+    #[test]
+    fn test_group_entries() {
+        let acl = sample();
+        let mut member = Credentials::root();
+        member.set_groups(&[50]).unwrap();
+        member.set_gid(200).unwrap();
+        member.set_uid(1002).unwrap();
+        assert!(acl.permits(&member, 1000, 100, ACCESS_READ | ACCESS_EXEC));
+        assert!(!acl.permits(&member, 1000, 100, ACCESS_WRITE));
+
+        // Matching a group entry that grants nothing does not fall through
+        // to "other".
+        let owning_group = Credentials::new(1003, 100);
+        assert!(!acl.permits(&owning_group, 1000, 100, ACCESS_EXEC));
+    }
+
+    #[test]
+    fn test_other_entry() {
+        let acl = sample();
+        let stranger = Credentials::new(2000, 2000);
+        assert!(!acl.permits(&stranger, 1000, 100, ACCESS_READ));
+        let open = Acl::parse(&encode(&[
+            entry(ACL_USER_OBJ, 7, ACL_UNDEFINED_ID),
+            entry(ACL_GROUP_OBJ, 5, ACL_UNDEFINED_ID),
+            entry(ACL_OTHER, 4, ACL_UNDEFINED_ID),
+        ]))
+        .unwrap();
+        assert!(open.permits(&stranger, 1000, 100, ACCESS_READ));
+        assert_eq!(open.mode_bits(), 0o754);
+    }
+}
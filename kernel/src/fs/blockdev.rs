@@ -2,7 +2,7 @@
 //!
 //! Provides a common interface for block-level storage devices.
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, collections::BTreeMap};
 
 use crate::error::KernelError;
 
@@ -27,29 +27,85 @@ pub trait BlockDevice: Send + Sync {
     fn flush(&mut self) -> Result<(), KernelError> {
         Ok(()) // Default: no-op
     }
+
+    /// Write blocks with Force Unit Access: on return they are on stable
+    /// storage rather than in a volatile write cache. Devices that can
+    /// mark a single write this way leave the rest of the cache alone.
+    fn write_blocks_fua(&mut self, start_block: u64, buffer: &[u8]) -> Result<(), KernelError> {
+        self.write_blocks(start_block, buffer)?;
+        self.flush() // Default: write, then flush everything
+    }
+
+    /// Write barrier: every write completed before it reaches stable
+    /// storage before any write issued after it.
+    ///
+    /// Writes complete before `write_blocks` returns, so only a volatile
+    /// write cache can reorder them; the default flushes it.
+    fn barrier(&mut self) -> Result<(), KernelError> {
+        self.flush()
+    }
+
+    /// Whether writes are refused
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Number of hardware submission queues the block layer may spread
+    /// CPUs over.
+    fn hw_queues(&self) -> usize {
+        1
+    }
+
+    /// Requests each hardware queue takes at once.
+    fn queue_depth(&self) -> usize {
+        32
+    }
 }
 
+/// Size of the pages a [`RamBlockDevice`] allocates its storage in.
+const RAM_PAGE_SIZE: usize = 4096;
+
 /// RAM-backed block device (for testing/ramdisk)
+///
+/// Storage is allocated a page at a time on first write; blocks that were
+/// never written read as zeros.
 pub struct RamBlockDevice {
     name: alloc::string::String,
     block_size: usize,
-    data: Vec<u8>,
+    block_count: u64,
+    pages: BTreeMap<u64, Box<[u8]>>,
 }
 
 impl RamBlockDevice {
     /// Create a new RAM block device
     pub fn new(name: alloc::string::String, block_size: usize, block_count: u64) -> Self {
-        let size = block_size * block_count as usize;
         Self {
             name,
             block_size,
-            data: alloc::vec![0u8; size],
+            block_count,
+            pages: BTreeMap::new(),
         }
     }
 
     /// Get total size in bytes
     pub fn size(&self) -> usize {
-        self.data.len()
+        self.block_size * self.block_count as usize
+    }
+
+    /// Bytes of storage allocated so far
+    pub fn allocated(&self) -> usize {
+        self.pages.len() * RAM_PAGE_SIZE
+    }
+
+    /// Byte offset of a transfer, checked against the device size.
+    fn byte_offset(&self, start_block: u64, len: usize) -> Result<usize, KernelError> {
+        (start_block as usize)
+            .checked_mul(self.block_size)
+            .filter(|start| start.checked_add(len).is_some_and(|end| end <= self.size()))
+            .ok_or(KernelError::InvalidArgument {
+                name: "block_range",
+                value: "out_of_bounds",
+            })
     }
 }
 
@@ -63,36 +119,40 @@ impl BlockDevice for RamBlockDevice {
     }
 
     fn block_count(&self) -> u64 {
-        (self.data.len() / self.block_size) as u64
+        self.block_count
     }
 
     fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<(), KernelError> {
-        let start_byte = start_block as usize * self.block_size;
-        let end_byte = start_byte + buffer.len();
-
-        if end_byte > self.data.len() {
-            return Err(KernelError::InvalidArgument {
-                name: "block_range",
-                value: "out_of_bounds",
-            });
+        let mut pos = self.byte_offset(start_block, buffer.len())?;
+        let mut done = 0;
+        while done < buffer.len() {
+            let offset = pos % RAM_PAGE_SIZE;
+            let n = (RAM_PAGE_SIZE - offset).min(buffer.len() - done);
+            let dest = &mut buffer[done..done + n];
+            match self.pages.get(&((pos / RAM_PAGE_SIZE) as u64)) {
+                Some(page) => dest.copy_from_slice(&page[offset..offset + n]),
+                None => dest.fill(0),
+            }
+            pos += n;
+            done += n;
         }
-
-        buffer.copy_from_slice(&self.data[start_byte..end_byte]);
         Ok(())
     }
 
     fn write_blocks(&mut self, start_block: u64, buffer: &[u8]) -> Result<(), KernelError> {
-        let start_byte = start_block as usize * self.block_size;
-        let end_byte = start_byte + buffer.len();
-
-        if end_byte > self.data.len() {
-            return Err(KernelError::InvalidArgument {
-                name: "block_range",
-                value: "out_of_bounds",
-            });
+        let mut pos = self.byte_offset(start_block, buffer.len())?;
+        let mut done = 0;
+        while done < buffer.len() {
+            let offset = pos % RAM_PAGE_SIZE;
+            let n = (RAM_PAGE_SIZE - offset).min(buffer.len() - done);
+            let page = self
+                .pages
+                .entry((pos / RAM_PAGE_SIZE) as u64)
+                .or_insert_with(|| alloc::vec![0u8; RAM_PAGE_SIZE].into_boxed_slice());
+            page[offset..offset + n].copy_from_slice(&buffer[done..done + n]);
+            pos += n;
+            done += n;
         }
-
-        self.data[start_byte..end_byte].copy_from_slice(buffer);
         Ok(())
     }
 }
@@ -120,4 +180,26 @@ mod tests {
 
         assert_eq!(read_data, write_data);
     }
+
+    #[test]
+    fn test_ram_block_device_sparse() {
+        let mut dev = RamBlockDevice::new(String::from("test"), 512, 64);
+        assert_eq!(dev.allocated(), 0);
+
+        // Unwritten blocks read as zeros without allocating
+        let mut buf = [0xFFu8; 1024];
+        dev.read_blocks(10, &mut buf).unwrap();
+        assert_eq!(buf, [0u8; 1024]);
+        assert_eq!(dev.allocated(), 0);
+
+        // A write straddling a page boundary allocates both pages
+        dev.write_blocks(7, &[0x42u8; 1024]).unwrap();
+        assert_eq!(dev.allocated(), 2 * RAM_PAGE_SIZE);
+        dev.read_blocks(6, &mut buf).unwrap();
+        assert_eq!(&buf[..512], &[0u8; 512][..]);
+        assert_eq!(&buf[512..], &[0x42u8; 512][..]);
+
+        assert!(dev.read_blocks(63, &mut buf).is_err());
+        assert!(dev.write_blocks(u64::MAX, &buf).is_err());
+    }
 }
@@ -5,6 +5,10 @@
 //! - Inode table for file/directory metadata
 //! - Block allocation bitmap
 //! - Data blocks for file content
+//! - One extended attribute block per inode (ext2 `i_file_acl` style)
+//! - Sparse files: unallocated logical blocks are holes that read as zeros
+//! - Format versions: v2 images (64-bit sizes, nanosecond timestamps) and v1
+//!   images are both mounted, and kept in their own format
 
 // Allow dead code for filesystem methods not yet called from higher layers
 #![allow(
@@ -16,7 +20,7 @@
 )]
 
 use alloc::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     string::{String, ToString},
     sync::Arc,
     vec,
@@ -24,6 +28,15 @@ use alloc::{
 };
 use core::mem::size_of;
 
+use blockfs_format::{
+    bitmap_blocks, computed_first_data_block, dir_entry_len, encode_dir_entry, inode_table_blocks,
+    inode_table_start, DirEntryHeader, FormatError, NANOS_PER_SEC, SUPERBLOCK_BLOCK,
+};
+pub use blockfs_format::{
+    DiskInode, InodeFormat, Superblock, BLOCKFS_MAGIC, BLOCK_SIZE, DIRECT_BLOCKS,
+    DIRECT_MAX_BLOCKS, DIR_ENTRY_HEADER_SIZE, DOUBLE_INDIRECT_MAX_BLOCKS, MAX_FILENAME_LEN,
+    PTRS_PER_BLOCK, SINGLE_INDIRECT_MAX_BLOCKS, XATTR_BLOCK_MAGIC,
+};
 use spin::Mutex;
 #[cfg(not(target_arch = "aarch64"))]
 use spin::RwLock;
@@ -31,67 +44,44 @@ use spin::RwLock;
 #[cfg(target_arch = "aarch64")]
 use super::bare_lock::RwLock;
 use super::{DirEntry, Filesystem, Metadata, NodeType, Permissions, VfsNode};
-use crate::error::{FsError, KernelError};
-
-/// Block size (4KB)
-pub const BLOCK_SIZE: usize = 4096;
-
-/// Number of direct block pointers in a DiskInode
-pub const DIRECT_BLOCKS: usize = 12;
-
-/// Number of block pointers that fit in one indirect block (4096 / 4 = 1024)
-pub const PTRS_PER_BLOCK: usize = BLOCK_SIZE / size_of::<u32>();
-
-/// Maximum file size addressable via direct blocks only: 12 * 4KB = 48KB
-pub const DIRECT_MAX_BLOCKS: usize = DIRECT_BLOCKS;
-
-/// Maximum file size addressable via direct + single indirect:
-/// 12 + 1024 = 1036 blocks = ~4MB
-pub const SINGLE_INDIRECT_MAX_BLOCKS: usize = DIRECT_BLOCKS + PTRS_PER_BLOCK;
-
-/// Maximum file size addressable via direct + single + double indirect:
-/// 12 + 1024 + 1024*1024 = 1_049_612 blocks = ~4GB
-pub const DOUBLE_INDIRECT_MAX_BLOCKS: usize =
-    DIRECT_BLOCKS + PTRS_PER_BLOCK + PTRS_PER_BLOCK * PTRS_PER_BLOCK;
-
-/// Magic number for BlockFS
-pub const BLOCKFS_MAGIC: u32 = 0x424C4B46; // "BLKF"
+use crate::{
+    drivers::block::mq::{BlockQueue, Op},
+    error::{FsError, KernelError},
+};
 
-/// Maximum filename length
-pub const MAX_FILENAME_LEN: usize = 255;
+// On-disk layouts, constants and layout math live in the `blockfs-format`
+// crate, shared with mkfs-blockfs.
 
 /// Number of 512-byte virtio sectors per 4KB BlockFS block
 const SECTORS_PER_BLOCK: usize = BLOCK_SIZE / 512;
 
-/// On-disk superblock lives in block 0
-const SUPERBLOCK_BLOCK: u32 = 0;
-
-/// Serialized superblock size in bytes (fixed layout, LE)
-const SUPERBLOCK_SERIALIZED_SIZE: usize = 62;
-
-/// On-disk DiskInode size (96 bytes, repr(C), no padding gaps)
-const DISK_INODE_SIZE: usize = 96;
-
-/// Number of DiskInodes that fit in one 4KB block
-const INODES_PER_BLOCK: usize = BLOCK_SIZE / DISK_INODE_SIZE; // 42
-
-/// Compute number of blocks needed for the block bitmap.
-/// Each byte covers 8 blocks, each block is 4096 bytes = 32768 bits.
-fn bitmap_blocks(total_blocks: u32) -> u32 {
-    let bits_needed = total_blocks as usize;
-    let bytes_needed = (bits_needed + 7) / 8;
-    ((bytes_needed + BLOCK_SIZE - 1) / BLOCK_SIZE) as u32
+/// Map a BlockFS format error onto the kernel's filesystem errors.
+fn format_error(err: FormatError) -> KernelError {
+    match err {
+        FormatError::NoSpace => KernelError::FsError(FsError::NoSpace),
+        FormatError::Truncated | FormatError::BadMagic => {
+            KernelError::FsError(FsError::CorruptedData)
+        }
+        FormatError::Unsupported => KernelError::FsError(FsError::NotSupported),
+    }
 }
 
-/// Compute number of blocks needed for the inode table.
-fn inode_table_blocks(inode_count: u32) -> u32 {
-    ((inode_count as usize * DISK_INODE_SIZE + BLOCK_SIZE - 1) / BLOCK_SIZE) as u32
+/// Current time for inode timestamps, in nanoseconds.
+fn now_ns() -> u64 {
+    crate::arch::timer::get_timestamp_ns()
 }
 
-/// Compute first_data_block from total_blocks and inode_count.
-/// Layout: [superblock(1)] [bitmap(N)] [inode_table(M)] [data...]
-fn computed_first_data_block(total_blocks: u32, inode_count: u32) -> u32 {
-    1 + bitmap_blocks(total_blocks) + inode_table_blocks(inode_count)
+/// A new inode of `mode` with every timestamp, creation time included, set
+/// to now.
+fn fresh_inode(mode: u16) -> DiskInode {
+    let now = now_ns();
+    DiskInode {
+        atime: now,
+        ctime: now,
+        mtime: now,
+        crtime: now,
+        ..DiskInode::new(mode, 0, 0)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -120,12 +110,46 @@ pub trait DiskBackend: Send + Sync {
 
     /// Whether the device is read-only.
     fn is_read_only(&self) -> bool;
+
+    /// Write a single 4KB block with Force Unit Access: on return it is on
+    /// stable storage, not in the device's write cache.
+    fn write_block_fua(&self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        self.write_block(block_num, data)?;
+        self.barrier()
+    }
+
+    /// Write barrier: every block written so far reaches stable storage
+    /// before any block written after it. The default suits backends
+    /// without a volatile write cache.
+    fn barrier(&self) -> Result<(), KernelError> {
+        Ok(())
+    }
 }
 
 /// Adapter that wraps the global virtio-blk device as a `DiskBackend`.
 ///
 /// Translates 4KB BlockFS blocks into 512-byte virtio sector reads/writes.
-pub struct VirtioBlockBackend;
+/// The filesystem occupies `sectors` sectors starting at `first_sector`,
+/// either the whole disk or one GPT partition.
+pub struct VirtioBlockBackend {
+    first_sector: u64,
+    sectors: u64,
+}
+
+impl VirtioBlockBackend {
+    /// A backend for the filesystem in the given sector range.
+    pub fn new(first_sector: u64, sectors: u64) -> Self {
+        Self {
+            first_sector,
+            sectors,
+        }
+    }
+
+    /// A backend spanning the whole disk.
+    pub fn whole_disk() -> Self {
+        Self::new(0, u64::MAX)
+    }
+}
 
 impl DiskBackend for VirtioBlockBackend {
     fn read_block(&self, block_num: u64, buf: &mut [u8]) -> Result<(), KernelError> {
@@ -142,7 +166,7 @@ impl DiskBackend for VirtioBlockBackend {
             })?;
         let mut device = device_lock.lock();
 
-        let base_sector = block_num * SECTORS_PER_BLOCK as u64;
+        let base_sector = self.first_sector + block_num * SECTORS_PER_BLOCK as u64;
         for i in 0..SECTORS_PER_BLOCK {
             let sector = base_sector + i as u64;
             let offset = i * 512;
@@ -166,7 +190,7 @@ impl DiskBackend for VirtioBlockBackend {
             })?;
         let mut device = device_lock.lock();
 
-        let base_sector = block_num * SECTORS_PER_BLOCK as u64;
+        let base_sector = self.first_sector + block_num * SECTORS_PER_BLOCK as u64;
         for i in 0..SECTORS_PER_BLOCK {
             let sector = base_sector + i as u64;
             let offset = i * 512;
@@ -176,11 +200,30 @@ impl DiskBackend for VirtioBlockBackend {
         Ok(())
     }
 
+    fn write_block_fua(&self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        // One flush for the whole block rather than one per sector
+        self.write_block(block_num, data)?;
+        self.barrier()
+    }
+
+    fn barrier(&self) -> Result<(), KernelError> {
+        crate::drivers::virtio::blk::get_device()
+            .ok_or(KernelError::NotInitialized {
+                subsystem: "virtio-blk",
+            })?
+            .lock()
+            .flush()
+    }
+
     fn block_count(&self) -> u64 {
         match crate::drivers::virtio::blk::get_device() {
             Some(lock) => {
                 let device = lock.lock();
-                device.capacity_sectors() / SECTORS_PER_BLOCK as u64
+                let available = device
+                    .capacity_sectors()
+                    .saturating_sub(self.first_sector)
+                    .min(self.sectors);
+                available / SECTORS_PER_BLOCK as u64
             }
             None => 0,
         }
@@ -197,124 +240,109 @@ impl DiskBackend for VirtioBlockBackend {
     }
 }
 
-/// Superblock structure
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct Superblock {
-    pub magic: u32,
-    pub block_count: u32,
-    pub inode_count: u32,
-    pub free_blocks: u32,
-    pub free_inodes: u32,
-    pub first_data_block: u32,
-    pub block_size: u32,
-    pub inode_size: u16,
-    pub blocks_per_group: u32,
-    pub inodes_per_group: u32,
-    pub mount_time: u64,
-    pub write_time: u64,
-    pub mount_count: u16,
-    pub max_mount_count: u16,
-    pub state: u16,
-    pub errors: u16,
+/// Adapter that exposes a registered block device (a loop or null device,
+/// for instance) as a `DiskBackend`, through its request queue.
+///
+/// One BlockFS block covers `BLOCK_SIZE / block_size` device blocks.
+pub struct BlockDeviceBackend {
+    queue: Arc<BlockQueue>,
 }
 
-impl Superblock {
-    pub fn new(block_count: u32, inode_count: u32) -> Self {
-        let first_data = computed_first_data_block(block_count, inode_count);
-        Self {
-            magic: BLOCKFS_MAGIC,
-            block_count,
-            inode_count,
-            free_blocks: block_count.saturating_sub(first_data),
-            free_inodes: inode_count - 1, // Reserve root inode
-            first_data_block: first_data,
-            block_size: BLOCK_SIZE as u32,
-            inode_size: size_of::<DiskInode>() as u16,
-            blocks_per_group: 8192,
-            inodes_per_group: 2048,
-            mount_time: 0,
-            write_time: 0,
-            mount_count: 0,
-            max_mount_count: 100,
-            state: 1, // Clean
-            errors: 0,
-        }
-    }
-
-    pub fn is_valid(&self) -> bool {
-        self.magic == BLOCKFS_MAGIC
+impl BlockDeviceBackend {
+    /// A backend spanning the whole of `device`, whose block size must
+    /// divide the BlockFS block size.
+    pub fn new(device: crate::drivers::block::SharedBlockDevice) -> Result<Self, KernelError> {
+        Self::with_queue(Arc::new(BlockQueue::new(device)))
     }
-}
 
-/// On-disk inode structure
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct DiskInode {
-    pub mode: u16,
-    pub uid: u16,
-    pub size: u32,
-    pub atime: u32,
-    pub ctime: u32,
-    pub mtime: u32,
-    pub dtime: u32,
-    pub gid: u16,
-    pub links_count: u16,
-    pub blocks: u32,
-    pub flags: u32,
-    pub direct_blocks: [u32; 12],
-    pub indirect_block: u32,
-    pub double_indirect_block: u32,
-    pub triple_indirect_block: u32,
+    /// A backend spanning the whole device behind `queue`.
+    pub fn with_queue(queue: Arc<BlockQueue>) -> Result<Self, KernelError> {
+        let block_size = queue.block_size();
+        if block_size == 0 || !BLOCK_SIZE.is_multiple_of(block_size) {
+            return Err(KernelError::InvalidArgument {
+                name: "block_size",
+                value: "does not divide 4096",
+            });
+        }
+        Ok(Self { queue })
+    }
+
+    /// First device block of BlockFS block `block_num`.
+    fn first_block(&self, block_num: u64) -> u64 {
+        block_num * (BLOCK_SIZE / self.queue.block_size()) as u64
+    }
 }
 
-impl DiskInode {
-    pub fn new(mode: u16, uid: u16, gid: u16) -> Self {
-        Self {
-            mode,
-            uid,
-            gid,
-            size: 0,
-            atime: 0,
-            ctime: 0,
-            mtime: 0,
-            dtime: 0,
-            links_count: 1,
-            blocks: 0,
-            flags: 0,
-            direct_blocks: [0; 12],
-            indirect_block: 0,
-            double_indirect_block: 0,
-            triple_indirect_block: 0,
+impl DiskBackend for BlockDeviceBackend {
+    fn read_block(&self, block_num: u64, buf: &mut [u8]) -> Result<(), KernelError> {
+        if buf.len() < BLOCK_SIZE {
+            return Err(KernelError::InvalidArgument {
+                name: "buf",
+                value: "buffer must be at least 4096 bytes",
+            });
+        }
+        let data =
+            self.queue
+                .execute(Op::Read, self.first_block(block_num), vec![0; BLOCK_SIZE])?;
+        buf[..BLOCK_SIZE].copy_from_slice(&data);
+        Ok(())
+    }
+
+    fn write_block(&self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        if data.len() < BLOCK_SIZE {
+            return Err(KernelError::InvalidArgument {
+                name: "data",
+                value: "data must be at least 4096 bytes",
+            });
         }
+        self.queue
+            .execute(
+                Op::Write,
+                self.first_block(block_num),
+                data[..BLOCK_SIZE].to_vec(),
+            )
+            .map(drop)
     }
 
-    pub fn is_dir(&self) -> bool {
-        (self.mode & 0x4000) != 0
+    fn write_block_fua(&self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+        if data.len() < BLOCK_SIZE {
+            return Err(KernelError::InvalidArgument {
+                name: "data",
+                value: "data must be at least 4096 bytes",
+            });
+        }
+        self.queue
+            .execute(
+                Op::WriteFua,
+                self.first_block(block_num),
+                data[..BLOCK_SIZE].to_vec(),
+            )
+            .map(drop)
     }
 
-    pub fn is_file(&self) -> bool {
-        (self.mode & 0x8000) != 0
+    fn barrier(&self) -> Result<(), KernelError> {
+        self.queue.execute(Op::Flush, 0, Vec::new()).map(drop)
     }
 
-    pub fn is_symlink(&self) -> bool {
-        (self.mode & 0xA000) == 0xA000
+    fn block_count(&self) -> u64 {
+        self.queue.device().lock().block_count() / (BLOCK_SIZE / self.queue.block_size()) as u64
     }
 
-    pub fn node_type(&self) -> NodeType {
-        if self.is_dir() {
-            NodeType::Directory
-        } else if self.is_symlink() {
-            NodeType::Symlink
-        } else {
-            NodeType::File
-        }
+    fn is_read_only(&self) -> bool {
+        self.queue.device().lock().is_read_only()
     }
 }
 
-/// Size of the fixed header in a DiskDirEntry (inode + rec_len + name_len +
-/// file_type)
-pub const DIR_ENTRY_HEADER_SIZE: usize = 8;
+/// VFS node type of an on-disk inode
+fn inode_node_type(inode: &DiskInode) -> NodeType {
+    if inode.is_dir() {
+        NodeType::Directory
+    } else if inode.is_symlink() {
+        NodeType::Symlink
+    } else {
+        NodeType::File
+    }
+}
 
 /// On-disk directory entry (ext2-style variable-length record)
 ///
@@ -336,17 +364,17 @@ pub struct DiskDirEntry {
 
 impl DiskDirEntry {
     /// File type constant for regular files
-    pub const FT_REG_FILE: u8 = 1;
+    pub const FT_REG_FILE: u8 = blockfs_format::FT_REG_FILE;
     /// File type constant for directories
-    pub const FT_DIR: u8 = 2;
+    pub const FT_DIR: u8 = blockfs_format::FT_DIR;
     /// File type constant for symlinks
-    pub const FT_SYMLINK: u8 = 7;
+    pub const FT_SYMLINK: u8 = blockfs_format::FT_SYMLINK;
 
     /// Create a new directory entry
     pub fn new(inode: u32, name: &str, file_type: u8) -> Self {
         let name_bytes = name.as_bytes();
         let name_len = name_bytes.len().min(MAX_FILENAME_LEN) as u8;
-        let rec_len = align4(DIR_ENTRY_HEADER_SIZE + name_len as usize) as u16;
+        let rec_len = dir_entry_len(name_len as usize) as u16;
 
         let mut entry = Self {
             inode,
@@ -377,11 +405,6 @@ impl DiskDirEntry {
     }
 }
 
-/// Align a value up to the next 4-byte boundary
-fn align4(val: usize) -> usize {
-    (val + 3) & !3
-}
-
 /// Block allocation bitmap
 pub struct BlockBitmap {
     bitmap: Vec<u8>,
@@ -446,6 +469,15 @@ impl BlockFsNode {
     pub fn new(inode_num: u32, fs: Arc<RwLock<BlockFsInner>>) -> Self {
         Self { inode_num, fs }
     }
+
+    /// Inode number of `node` if it belongs to this filesystem instance.
+    fn same_fs_inode(&self, node: &dyn VfsNode) -> Result<u32, KernelError> {
+        node.as_any()
+            .and_then(|any| any.downcast_ref::<BlockFsNode>())
+            .filter(|other| Arc::ptr_eq(&other.fs, &self.fs))
+            .map(|other| other.inode_num)
+            .ok_or(KernelError::FsError(FsError::CrossDevice))
+    }
 }
 
 impl VfsNode for BlockFsNode {
@@ -530,15 +562,84 @@ impl VfsNode for BlockFsNode {
         fs.chmod_inode(self.inode_num, permissions)
     }
 
+    fn chown(&self, uid: u32, gid: u32) -> Result<(), KernelError> {
+        let mut fs = self.fs.write();
+        fs.chown_inode(self.inode_num, uid, gid)
+    }
+
     fn link(&self, name: &str, target: Arc<dyn VfsNode>) -> Result<(), KernelError> {
+        let target_inode = self.same_fs_inode(&*target)?;
+        let mut fs = self.fs.write();
+        fs.link_in_dir(self.inode_num, name, target_inode)
+    }
+
+    fn link_count(&self) -> u32 {
+        let fs = self.fs.read();
+        fs.inode_table
+            .get(self.inode_num as usize)
+            .map_or(0, |inode| inode.links_count as u32)
+    }
+
+    fn rename(
+        &self,
+        old_name: &str,
+        new_dir: &dyn VfsNode,
+        new_name: &str,
+    ) -> Result<(), KernelError> {
+        let new_dir_inode = self.same_fs_inode(new_dir)?;
+        // One write lock covers both directories, so the move is atomic with
+        // respect to every other BlockFS operation.
         let mut fs = self.fs.write();
-        fs.link_in_dir(self.inode_num, name, target)
+        fs.rename_entry(self.inode_num, old_name, new_dir_inode, new_name)
+    }
+
+    fn seek_data(&self, offset: usize) -> Result<Option<usize>, KernelError> {
+        let fs = self.fs.read();
+        fs.seek_extent(self.inode_num, offset, true)
+    }
+
+    fn seek_hole(&self, offset: usize) -> Result<Option<usize>, KernelError> {
+        let fs = self.fs.read();
+        fs.seek_extent(self.inode_num, offset, false)
+    }
+
+    fn fallocate(&self, mode: u32, offset: usize, len: usize) -> Result<(), KernelError> {
+        let mut fs = self.fs.write();
+        fs.fallocate_inode(self.inode_num, mode, offset, len)
+    }
+
+    fn get_xattr(&self, name: &str) -> Result<Vec<u8>, KernelError> {
+        let fs = self.fs.read();
+        fs.read_xattrs(self.inode_num)?
+            .remove(name)
+            .ok_or(KernelError::FsError(FsError::NotFound))
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: u32) -> Result<(), KernelError> {
+        let mut fs = self.fs.write();
+        fs.set_xattr_inode(self.inode_num, name, value, flags)
+    }
+
+    fn list_xattr(&self) -> Result<Vec<String>, KernelError> {
+        let fs = self.fs.read();
+        Ok(fs.read_xattrs(self.inode_num)?.into_keys().collect())
+    }
+
+    fn remove_xattr(&self, name: &str) -> Result<(), KernelError> {
+        let mut fs = self.fs.write();
+        fs.remove_xattr_inode(self.inode_num, name)
+    }
+
+    fn as_any(&self) -> Option<&dyn core::any::Any> {
+        Some(self)
     }
 }
 
 /// Internal BlockFS state
 pub struct BlockFsInner {
     superblock: Superblock,
+    /// Inode table layout, from the superblock's version and features
+    format: InodeFormat,
     block_bitmap: BlockBitmap,
     inode_table: Vec<DiskInode>,
     block_data: Vec<Vec<u8>>, // In-memory block storage (RAM cache)
@@ -553,8 +654,9 @@ pub struct BlockFsInner {
 
 impl BlockFsInner {
     pub fn new(block_count: u32, inode_count: u32) -> Self {
-        let first_data = computed_first_data_block(block_count, inode_count);
-        let mut superblock = Superblock::new(block_count, inode_count);
+        let format = InodeFormat::V2;
+        let first_data = computed_first_data_block(block_count, inode_count, format);
+        let mut superblock = Superblock::with_format(block_count, inode_count, format);
         superblock.first_data_block = first_data;
         superblock.free_blocks = block_count.saturating_sub(first_data);
 
@@ -566,12 +668,12 @@ impl BlockFsInner {
         }
 
         let mut inode_table = Vec::new();
-        inode_table.resize(inode_count as usize, DiskInode::new(0, 0, 0));
+        inode_table.resize(inode_count as usize, DiskInode::default());
 
         // Initialize root directory (inode 0)
         // links_count = 2: one for itself (".") and one from the parent (root is its
         // own parent)
-        let mut root_inode = DiskInode::new(0x41ED, 0, 0); // Directory, rwxr-xr-x
+        let mut root_inode = fresh_inode(0x41ED); // Directory, rwxr-xr-x
         root_inode.links_count = 2;
         inode_table[0] = root_inode;
 
@@ -583,6 +685,7 @@ impl BlockFsInner {
 
         let mut fs = Self {
             superblock,
+            format,
             block_bitmap,
             inode_table,
             block_data,
@@ -618,10 +721,15 @@ impl BlockFsInner {
         None
     }
 
+    /// Allocate a zero-filled block.
+    ///
+    /// Reused blocks are cleared so that the unwritten part of a block
+    /// allocated inside a former hole still reads as zeros.
     fn allocate_block(&mut self) -> Option<u32> {
         let block = self.block_bitmap.allocate_block()?;
         self.superblock.free_blocks -= 1;
         self.materialize_block(block as usize);
+        self.block_data[block as usize].fill(0);
         Some(block)
     }
 
@@ -659,6 +767,12 @@ impl BlockFsInner {
 
     /// Sync all dirty blocks and metadata to the disk backend.
     ///
+    /// Writes are ordered with barriers: data blocks reach the disk before
+    /// the inodes pointing at them, and the bitmap and inode table before
+    /// the superblock, which goes last with Force Unit Access. Once `sync`
+    /// returns everything is on stable storage, without the device cache
+    /// having been flushed once more at the end.
+    ///
     /// If no disk backend is configured, this is a no-op. Returns the number
     /// of blocks synced on success.
     fn sync_to_disk(&mut self) -> Result<usize, KernelError> {
@@ -700,14 +814,18 @@ impl BlockFsInner {
 
         // Clear dirty set after successful write
         self.dirty_blocks.clear();
+        if synced > 0 {
+            backend.barrier()?;
+        }
 
         // Update superblock write time and mount count
         self.superblock.write_time = crate::arch::timer::read_hw_timestamp();
 
-        // Write metadata (superblock, bitmap, inode table)
-        self.serialize_superblock(&*backend)?;
+        // Write metadata: bitmap and inode table, then the superblock
         self.serialize_bitmap(&*backend)?;
         self.serialize_inode_table(&*backend)?;
+        backend.barrier()?;
+        self.serialize_superblock(&*backend)?;
         synced += 1; // Count metadata as one sync unit
 
         Ok(synced)
@@ -743,29 +861,12 @@ impl BlockFsInner {
 
     // --- On-disk metadata serialization ---
 
-    /// Serialize the superblock to block 0 on disk (62 bytes, LE).
+    /// Serialize the superblock to block 0 on disk (76 bytes, LE).
     fn serialize_superblock(&self, backend: &dyn DiskBackend) -> Result<(), KernelError> {
         let mut buf = [0u8; BLOCK_SIZE];
-        let sb = &self.superblock;
-
-        buf[0..4].copy_from_slice(&sb.magic.to_le_bytes());
-        buf[4..8].copy_from_slice(&sb.block_count.to_le_bytes());
-        buf[8..12].copy_from_slice(&sb.inode_count.to_le_bytes());
-        buf[12..16].copy_from_slice(&sb.free_blocks.to_le_bytes());
-        buf[16..20].copy_from_slice(&sb.free_inodes.to_le_bytes());
-        buf[20..24].copy_from_slice(&sb.first_data_block.to_le_bytes());
-        buf[24..28].copy_from_slice(&sb.block_size.to_le_bytes());
-        buf[28..30].copy_from_slice(&sb.inode_size.to_le_bytes());
-        buf[30..34].copy_from_slice(&sb.blocks_per_group.to_le_bytes());
-        buf[34..38].copy_from_slice(&sb.inodes_per_group.to_le_bytes());
-        buf[38..46].copy_from_slice(&sb.mount_time.to_le_bytes());
-        buf[46..54].copy_from_slice(&sb.write_time.to_le_bytes());
-        buf[54..56].copy_from_slice(&sb.mount_count.to_le_bytes());
-        buf[56..58].copy_from_slice(&sb.max_mount_count.to_le_bytes());
-        buf[58..60].copy_from_slice(&sb.state.to_le_bytes());
-        buf[60..62].copy_from_slice(&sb.errors.to_le_bytes());
-
-        backend.write_block(SUPERBLOCK_BLOCK as u64, &buf)
+        self.superblock.encode(&mut buf);
+
+        backend.write_block_fua(SUPERBLOCK_BLOCK as u64, &buf)
     }
 
     /// Deserialize the superblock from block 0. Returns the parsed superblock.
@@ -773,33 +874,12 @@ impl BlockFsInner {
         let mut buf = [0u8; BLOCK_SIZE];
         backend.read_block(SUPERBLOCK_BLOCK as u64, &mut buf)?;
 
-        let magic = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
-        if magic != BLOCKFS_MAGIC {
+        let superblock = Superblock::decode(&buf);
+        if !superblock.is_valid() {
             return Err(KernelError::FsError(FsError::CorruptedData));
         }
 
-        Ok(Superblock {
-            magic,
-            block_count: u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
-            inode_count: u32::from_le_bytes([buf[8], buf[9], buf[10], buf[11]]),
-            free_blocks: u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]),
-            free_inodes: u32::from_le_bytes([buf[16], buf[17], buf[18], buf[19]]),
-            first_data_block: u32::from_le_bytes([buf[20], buf[21], buf[22], buf[23]]),
-            block_size: u32::from_le_bytes([buf[24], buf[25], buf[26], buf[27]]),
-            inode_size: u16::from_le_bytes([buf[28], buf[29]]),
-            blocks_per_group: u32::from_le_bytes([buf[30], buf[31], buf[32], buf[33]]),
-            inodes_per_group: u32::from_le_bytes([buf[34], buf[35], buf[36], buf[37]]),
-            mount_time: u64::from_le_bytes([
-                buf[38], buf[39], buf[40], buf[41], buf[42], buf[43], buf[44], buf[45],
-            ]),
-            write_time: u64::from_le_bytes([
-                buf[46], buf[47], buf[48], buf[49], buf[50], buf[51], buf[52], buf[53],
-            ]),
-            mount_count: u16::from_le_bytes([buf[54], buf[55]]),
-            max_mount_count: u16::from_le_bytes([buf[56], buf[57]]),
-            state: u16::from_le_bytes([buf[58], buf[59]]),
-            errors: u16::from_le_bytes([buf[60], buf[61]]),
-        })
+        Ok(superblock)
     }
 
     /// Serialize the block bitmap to disk (blocks 1..1+bitmap_blocks).
@@ -851,74 +931,24 @@ impl BlockFsInner {
         })
     }
 
-    /// Serialize a single DiskInode to 96 bytes (LE).
-    fn serialize_disk_inode(inode: &DiskInode, buf: &mut [u8]) {
-        buf[0..2].copy_from_slice(&inode.mode.to_le_bytes());
-        buf[2..4].copy_from_slice(&inode.uid.to_le_bytes());
-        buf[4..8].copy_from_slice(&inode.size.to_le_bytes());
-        buf[8..12].copy_from_slice(&inode.atime.to_le_bytes());
-        buf[12..16].copy_from_slice(&inode.ctime.to_le_bytes());
-        buf[16..20].copy_from_slice(&inode.mtime.to_le_bytes());
-        buf[20..24].copy_from_slice(&inode.dtime.to_le_bytes());
-        buf[24..26].copy_from_slice(&inode.gid.to_le_bytes());
-        buf[26..28].copy_from_slice(&inode.links_count.to_le_bytes());
-        buf[28..32].copy_from_slice(&inode.blocks.to_le_bytes());
-        buf[32..36].copy_from_slice(&inode.flags.to_le_bytes());
-        for (j, &blk) in inode.direct_blocks.iter().enumerate() {
-            let off = 36 + j * 4;
-            buf[off..off + 4].copy_from_slice(&blk.to_le_bytes());
-        }
-        buf[84..88].copy_from_slice(&inode.indirect_block.to_le_bytes());
-        buf[88..92].copy_from_slice(&inode.double_indirect_block.to_le_bytes());
-        buf[92..96].copy_from_slice(&inode.triple_indirect_block.to_le_bytes());
-    }
-
-    /// Deserialize a single DiskInode from 96 bytes (LE).
-    fn deserialize_disk_inode(buf: &[u8]) -> DiskInode {
-        let mut direct_blocks = [0u32; 12];
-        for (j, block) in direct_blocks.iter_mut().enumerate() {
-            let off = 36 + j * 4;
-            *block = u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]]);
-        }
-        DiskInode {
-            mode: u16::from_le_bytes([buf[0], buf[1]]),
-            uid: u16::from_le_bytes([buf[2], buf[3]]),
-            size: u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
-            atime: u32::from_le_bytes([buf[8], buf[9], buf[10], buf[11]]),
-            ctime: u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]),
-            mtime: u32::from_le_bytes([buf[16], buf[17], buf[18], buf[19]]),
-            dtime: u32::from_le_bytes([buf[20], buf[21], buf[22], buf[23]]),
-            gid: u16::from_le_bytes([buf[24], buf[25]]),
-            links_count: u16::from_le_bytes([buf[26], buf[27]]),
-            blocks: u32::from_le_bytes([buf[28], buf[29], buf[30], buf[31]]),
-            flags: u32::from_le_bytes([buf[32], buf[33], buf[34], buf[35]]),
-            direct_blocks,
-            indirect_block: u32::from_le_bytes([buf[84], buf[85], buf[86], buf[87]]),
-            double_indirect_block: u32::from_le_bytes([buf[88], buf[89], buf[90], buf[91]]),
-            triple_indirect_block: u32::from_le_bytes([buf[92], buf[93], buf[94], buf[95]]),
-        }
-    }
-
     /// Serialize the entire inode table to disk.
     fn serialize_inode_table(&self, backend: &dyn DiskBackend) -> Result<(), KernelError> {
-        let bm_blocks = bitmap_blocks(self.superblock.block_count);
-        let inode_start = SUPERBLOCK_BLOCK + 1 + bm_blocks;
-        let it_blocks = inode_table_blocks(self.superblock.inode_count);
+        let inode_start = inode_table_start(self.superblock.block_count);
+        let it_blocks = inode_table_blocks(self.superblock.inode_count, self.format);
+        let per_block = self.format.inodes_per_block();
+        let inode_size = self.format.inode_size();
 
         for blk_idx in 0..it_blocks {
             let mut buf = [0u8; BLOCK_SIZE];
-            let base_inode = blk_idx as usize * INODES_PER_BLOCK;
+            let base_inode = blk_idx as usize * per_block;
 
-            for slot in 0..INODES_PER_BLOCK {
+            for slot in 0..per_block {
                 let inode_idx = base_inode + slot;
                 if inode_idx >= self.inode_table.len() {
                     break;
                 }
-                let off = slot * DISK_INODE_SIZE;
-                Self::serialize_disk_inode(
-                    &self.inode_table[inode_idx],
-                    &mut buf[off..off + DISK_INODE_SIZE],
-                );
+                let off = slot * inode_size;
+                self.inode_table[inode_idx].encode(&mut buf[off..off + inode_size], self.format);
             }
 
             backend.write_block((inode_start + blk_idx) as u64, &buf)?;
@@ -932,25 +962,25 @@ impl BlockFsInner {
         backend: &dyn DiskBackend,
         inode_count: u32,
         total_blocks: u32,
+        format: InodeFormat,
     ) -> Result<Vec<DiskInode>, KernelError> {
-        let bm_blocks = bitmap_blocks(total_blocks);
-        let inode_start = SUPERBLOCK_BLOCK + 1 + bm_blocks;
-        let it_blocks = inode_table_blocks(inode_count);
+        let inode_start = inode_table_start(total_blocks);
+        let it_blocks = inode_table_blocks(inode_count, format);
+        let per_block = format.inodes_per_block();
+        let inode_size = format.inode_size();
         let mut inode_table = Vec::with_capacity(inode_count as usize);
 
         for blk_idx in 0..it_blocks {
             let mut buf = [0u8; BLOCK_SIZE];
             backend.read_block((inode_start + blk_idx) as u64, &mut buf)?;
 
-            for slot in 0..INODES_PER_BLOCK {
-                let inode_idx = blk_idx as usize * INODES_PER_BLOCK + slot;
+            for slot in 0..per_block {
+                let inode_idx = blk_idx as usize * per_block + slot;
                 if inode_idx >= inode_count as usize {
                     break;
                 }
-                let off = slot * DISK_INODE_SIZE;
-                inode_table.push(Self::deserialize_disk_inode(
-                    &buf[off..off + DISK_INODE_SIZE],
-                ));
+                let off = slot * inode_size;
+                inode_table.push(DiskInode::decode(&buf[off..off + inode_size], format));
             }
         }
 
@@ -960,24 +990,50 @@ impl BlockFsInner {
     /// Load an existing BlockFS from a disk backend.
     ///
     /// Reads superblock, bitmap, inode table, and all data blocks.
+    ///
+    /// Images with incompatible features this driver does not know are
+    /// refused, as are images with unknown read-only compatible features
+    /// unless the device is read-only.
     fn load_existing(backend: Arc<Mutex<dyn DiskBackend>>) -> Result<Self, KernelError> {
         let bk = backend.lock();
 
         // Read and validate superblock
         let superblock = Self::deserialize_superblock(&*bk)?;
         crate::println!(
-            "[BLOCKFS] Found existing filesystem: {} blocks, {} inodes, first_data={}",
+            "[BLOCKFS] Found existing filesystem: v{}, {} blocks, {} inodes, first_data={}",
+            superblock.version,
             superblock.block_count,
             superblock.inode_count,
             superblock.first_data_block
         );
 
+        let format = superblock.inode_format().map_err(|e| {
+            crate::println!(
+                "[BLOCKFS] Unsupported format: version {}, incompat features {:#x}, inode size {}",
+                superblock.version,
+                superblock.feature_incompat,
+                superblock.inode_size
+            );
+            format_error(e)
+        })?;
+        if !superblock.is_writable() && !bk.is_read_only() {
+            crate::println!(
+                "[BLOCKFS] Unknown ro_compat features {:#x}: mount from a read-only device",
+                superblock.feature_ro_compat
+            );
+            return Err(KernelError::FsError(FsError::NotSupported));
+        }
+
         // Read bitmap
         let block_bitmap = Self::deserialize_bitmap(&*bk, superblock.block_count)?;
 
         // Read inode table
-        let inode_table =
-            Self::deserialize_inode_table(&*bk, superblock.inode_count, superblock.block_count)?;
+        let inode_table = Self::deserialize_inode_table(
+            &*bk,
+            superblock.inode_count,
+            superblock.block_count,
+            format,
+        )?;
 
         // Allocate sparse in-memory block storage and read only allocated blocks
         let block_count = superblock.block_count as usize;
@@ -1016,6 +1072,7 @@ impl BlockFsInner {
 
         let mut fs = Self {
             superblock,
+            format,
             block_bitmap,
             inode_table,
             block_data,
@@ -1201,11 +1258,12 @@ impl BlockFsInner {
             .get(inode_num as usize)
             .ok_or(KernelError::FsError(FsError::NotFound))?;
 
-        if offset >= inode.size as usize {
+        let size = inode.size as usize;
+        if offset >= size {
             return Ok(0);
         }
 
-        let to_read = buffer.len().min(inode.size as usize - offset);
+        let to_read = buffer.len().min(size - offset);
         let mut bytes_read = 0;
         let mut current_offset = offset;
 
@@ -1243,6 +1301,13 @@ impl BlockFsInner {
         offset: usize,
         data: &[u8],
     ) -> Result<usize, KernelError> {
+        // Clip the write at the largest size the inode format can record
+        let max_size = self.format.max_file_size() as usize;
+        if offset >= max_size && !data.is_empty() {
+            return Err(KernelError::FsError(FsError::FileTooLarge));
+        }
+        let data = &data[..data.len().min(max_size.saturating_sub(offset))];
+
         // Collect block information in multiple passes to avoid borrow conflicts
         let mut blocks_needed: Vec<(usize, usize, usize)> = Vec::new();
         let mut current_offset = offset;
@@ -1282,8 +1347,9 @@ impl BlockFsInner {
         }
 
         // Update inode size
-        if (offset + bytes_written) > self.inode_table[inode_num as usize].size as usize {
-            self.inode_table[inode_num as usize].size = (offset + bytes_written) as u32;
+        let end = (offset + bytes_written) as u64;
+        if end > self.inode_table[inode_num as usize].size {
+            self.inode_table[inode_num as usize].size = end;
         }
 
         Ok(bytes_written)
@@ -1296,14 +1362,19 @@ impl BlockFsInner {
             .ok_or(KernelError::FsError(FsError::NotFound))?;
 
         Ok(Metadata {
-            node_type: inode.node_type(),
+            node_type: inode_node_type(inode),
             size: inode.size as usize,
             permissions: Permissions::from_mode(inode.mode as u32),
             uid: inode.uid as u32,
             gid: inode.gid as u32,
-            created: inode.ctime as u64,
-            modified: inode.mtime as u64,
-            accessed: inode.atime as u64,
+            // Creation time is unknown (0) in v1 inodes
+            created: if inode.crtime != 0 {
+                inode.crtime
+            } else {
+                inode.ctime
+            } / NANOS_PER_SEC,
+            modified: inode.mtime / NANOS_PER_SEC,
+            accessed: inode.atime / NANOS_PER_SEC,
             inode: inode_num as u64,
         })
     }
@@ -1405,7 +1476,7 @@ impl BlockFsInner {
             .ok_or(KernelError::ResourceExhausted { resource: "inodes" })?;
 
         let mode = permissions_to_mode(permissions, false);
-        self.inode_table[inode_num as usize] = DiskInode::new(mode, 0, 0);
+        self.inode_table[inode_num as usize] = fresh_inode(mode);
 
         // Add directory entry to parent
         if let Err(e) = self.write_dir_entry(parent, inode_num, name, DiskDirEntry::FT_REG_FILE) {
@@ -1442,7 +1513,7 @@ impl BlockFsInner {
             .ok_or(KernelError::ResourceExhausted { resource: "inodes" })?;
 
         let mode = permissions_to_mode(permissions, true);
-        let mut new_inode = DiskInode::new(mode, 0, 0);
+        let mut new_inode = fresh_inode(mode);
         // Directories start with link count 2 (parent's entry + self ".")
         new_inode.links_count = 2;
         self.inode_table[inode_num as usize] = new_inode;
@@ -1508,9 +1579,9 @@ impl BlockFsInner {
             .ok_or(KernelError::ResourceExhausted { resource: "inodes" })?;
 
         // Symlink inode: mode 0o120000 | 0o777 (rwx for all)
-        let mut inode = DiskInode::new(0o120000 | 0o777, 0, 0);
+        let mut inode = fresh_inode(0o120000 | 0o777);
         inode.links_count = 1;
-        inode.size = target.len() as u32;
+        inode.size = target.len() as u64;
         self.inode_table[inode_num as usize] = inode;
 
         // Store target contents as file data
@@ -1577,27 +1648,285 @@ impl BlockFsInner {
         block[offset + 3] = 0;
         self.mark_dirty(block_num);
 
-        // Decrement link count on the target inode
-        if let Some(target) = self.inode_table.get_mut(target_inode as usize) {
-            if target.links_count > 0 {
-                target.links_count -= 1;
+        self.release_link(parent, target_inode, is_dir);
+        Ok(())
+    }
+
+    /// Drop one directory entry's reference to `target_inode`, freeing its
+    /// blocks when no links remain.
+    ///
+    /// A removed directory also loses its "." link, and `parent` loses the
+    /// link held by the directory's "..".
+    fn release_link(&mut self, parent: u32, target_inode: u32, is_dir: bool) {
+        let Some(target) = self.inode_table.get_mut(target_inode as usize) else {
+            return;
+        };
+        if is_dir {
+            target.links_count = 0;
+            if let Some(p) = self.inode_table.get_mut(parent as usize) {
+                p.links_count = p.links_count.saturating_sub(1);
             }
+        } else {
+            target.links_count = target.links_count.saturating_sub(1);
+        }
 
-            // If unlinking a directory, also decrement parent link count (for "..")
-            if is_dir {
-                if let Some(p) = self.inode_table.get_mut(parent as usize) {
-                    if p.links_count > 0 {
-                        p.links_count -= 1;
-                    }
-                }
+        if self.inode_table[target_inode as usize].links_count == 0 {
+            self.free_inode_blocks(target_inode);
+        }
+    }
+
+    /// Point the directory entry at (`block_idx`, `offset`) of `dir_inode` at
+    /// a different inode.
+    fn retarget_dir_entry(
+        &mut self,
+        dir_inode: u32,
+        block_idx: usize,
+        offset: usize,
+        inode_num: u32,
+        file_type: u8,
+    ) -> Result<(), KernelError> {
+        let block_num = self.inode_table[dir_inode as usize].direct_blocks[block_idx];
+        if block_num == 0 {
+            return Err(KernelError::FsError(FsError::IoError));
+        }
+
+        self.materialize_block(block_num as usize);
+        let block = &mut self.block_data[block_num as usize];
+        block[offset..offset + 4].copy_from_slice(&inode_num.to_le_bytes());
+        block[offset + 7] = file_type;
+        self.mark_dirty(block_num);
+        Ok(())
+    }
+
+    /// Whether `dir_inode` is `ancestor` or lies beneath it.
+    fn is_within(&self, dir_inode: u32, ancestor: u32) -> bool {
+        let mut current = dir_inode;
+        // Bounded by the inode count in case of a corrupt ".." cycle.
+        for _ in 0..self.inode_table.len() {
+            if current == ancestor {
+                return true;
             }
+            match self.find_dir_entry(current, "..") {
+                Some((entry, _, _)) if entry.inode != current => current = entry.inode,
+                _ => return false,
+            }
+        }
+        false
+    }
 
-            // If links reach 0, free all data blocks
-            if self.inode_table[target_inode as usize].links_count == 0 {
-                self.free_inode_blocks(target_inode);
+    /// Move entry `old_name` of `old_dir` to `new_name` in `new_dir`.
+    ///
+    /// Every check runs before the first on-disk change, and an existing
+    /// target is replaced by rewriting its entry in place, so callers never
+    /// observe a state where `new_name` is missing.
+    fn rename_entry(
+        &mut self,
+        old_dir: u32,
+        old_name: &str,
+        new_dir: u32,
+        new_name: &str,
+    ) -> Result<(), KernelError> {
+        for name in [old_name, new_name] {
+            if name == "." || name == ".." {
+                return Err(KernelError::InvalidArgument {
+                    name: "filename",
+                    value: "cannot rename . or ..",
+                });
             }
         }
+        if new_name.is_empty() || new_name.len() > MAX_FILENAME_LEN {
+            return Err(KernelError::InvalidArgument {
+                name: "filename",
+                value: "empty or exceeds maximum length",
+            });
+        }
+        if !self
+            .inode_table
+            .get(new_dir as usize)
+            .is_some_and(|inode| inode.is_dir())
+        {
+            return Err(KernelError::FsError(FsError::NotADirectory));
+        }
+
+        let (source, src_block_idx, src_offset) = self
+            .find_dir_entry(old_dir, old_name)
+            .ok_or(KernelError::FsError(FsError::NotFound))?;
+        let source_is_dir = source.file_type == DiskDirEntry::FT_DIR;
+
+        if source_is_dir && self.is_within(new_dir, source.inode) {
+            return Err(KernelError::InvalidArgument {
+                name: "rename",
+                value: "cannot move a directory into itself",
+            });
+        }
 
+        let existing = self.find_dir_entry(new_dir, new_name);
+        if let Some((target, block_idx, offset)) = existing {
+            // Both names already refer to the same inode: nothing to do.
+            if target.inode == source.inode {
+                return Ok(());
+            }
+            let target_is_dir = target.file_type == DiskDirEntry::FT_DIR;
+            if source_is_dir && !target_is_dir {
+                return Err(KernelError::FsError(FsError::NotADirectory));
+            }
+            if !source_is_dir && target_is_dir {
+                return Err(KernelError::FsError(FsError::IsADirectory));
+            }
+            if target_is_dir
+                && self
+                    .readdir(target.inode)?
+                    .iter()
+                    .any(|e| e.name != "." && e.name != "..")
+            {
+                return Err(KernelError::FsError(FsError::DirectoryNotEmpty));
+            }
+
+            self.retarget_dir_entry(new_dir, block_idx, offset, source.inode, source.file_type)?;
+            self.release_link(new_dir, target.inode, target_is_dir);
+        } else {
+            self.write_dir_entry(new_dir, source.inode, new_name, source.file_type)?;
+        }
+
+        // Drop the old name. Appending never moves existing entries, so the
+        // recorded position is still valid.
+        self.retarget_dir_entry(old_dir, src_block_idx, src_offset, 0, source.file_type)?;
+
+        if source_is_dir && old_dir != new_dir {
+            if let Some((_, block_idx, offset)) = self.find_dir_entry(source.inode, "..") {
+                self.retarget_dir_entry(
+                    source.inode,
+                    block_idx,
+                    offset,
+                    new_dir,
+                    DiskDirEntry::FT_DIR,
+                )?;
+            }
+            // The moved directory's ".." link follows it to the new parent.
+            self.inode_table[new_dir as usize].links_count += 1;
+            let old_parent = &mut self.inode_table[old_dir as usize];
+            old_parent.links_count = old_parent.links_count.saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    /// Find the first data (`want_data`) or hole offset at or after `offset`
+    /// for `SEEK_DATA` / `SEEK_HOLE`.
+    ///
+    /// End of file counts as a hole. Returns `None` if `offset` is at or
+    /// beyond end of file, or if no data follows it.
+    fn seek_extent(
+        &self,
+        inode_num: u32,
+        offset: usize,
+        want_data: bool,
+    ) -> Result<Option<usize>, KernelError> {
+        let inode = self
+            .inode_table
+            .get(inode_num as usize)
+            .ok_or(KernelError::FsError(FsError::NotFound))?;
+        let size = inode.size as usize;
+        if offset >= size {
+            return Ok(None);
+        }
+
+        let last_block = (size - 1) / BLOCK_SIZE;
+        for logical_block in offset / BLOCK_SIZE..=last_block {
+            if self.resolve_block(inode, logical_block).is_some() == want_data {
+                return Ok(Some((logical_block * BLOCK_SIZE).max(offset)));
+            }
+        }
+        Ok(if want_data { None } else { Some(size) })
+    }
+
+    /// Unmap one logical block of an inode, leaving a hole. Indirect blocks
+    /// stay allocated and are released on truncate or unlink.
+    fn unmap_block(&mut self, inode_num: u32, logical_block: usize) {
+        let inode = self.inode_table[inode_num as usize];
+        let Some(block_num) = self.resolve_block(&inode, logical_block) else {
+            return;
+        };
+
+        if logical_block < DIRECT_BLOCKS {
+            self.inode_table[inode_num as usize].direct_blocks[logical_block] = 0;
+        } else if logical_block < SINGLE_INDIRECT_MAX_BLOCKS {
+            self.write_block_ptr(inode.indirect_block, logical_block - DIRECT_BLOCKS, 0);
+        } else {
+            let rel = logical_block - SINGLE_INDIRECT_MAX_BLOCKS;
+            let l1_block = self.read_block_ptr(inode.double_indirect_block, rel / PTRS_PER_BLOCK);
+            self.write_block_ptr(l1_block, rel % PTRS_PER_BLOCK, 0);
+        }
+
+        self.free_block(block_num);
+        let inode = &mut self.inode_table[inode_num as usize];
+        inode.blocks = inode.blocks.saturating_sub(1);
+    }
+
+    /// Zero `len` bytes at `offset` within an allocated block; holes are
+    /// already zero.
+    fn zero_in_block(&mut self, inode_num: u32, offset: usize, len: usize) {
+        let inode = &self.inode_table[inode_num as usize];
+        if let Some(block_num) = self.resolve_block(inode, offset / BLOCK_SIZE) {
+            let start = offset % BLOCK_SIZE;
+            self.materialize_block(block_num as usize);
+            self.block_data[block_num as usize][start..start + len].fill(0);
+            self.mark_dirty(block_num);
+        }
+    }
+
+    /// `fallocate` for an inode.
+    ///
+    /// Mode 0 allocates (zero-filled) blocks for the range and extends the
+    /// file; `FALLOC_FL_KEEP_SIZE` keeps the size. `FALLOC_FL_PUNCH_HOLE |
+    /// FALLOC_FL_KEEP_SIZE` frees the whole blocks in the range and zeroes
+    /// the partial blocks at either end.
+    fn fallocate_inode(
+        &mut self,
+        inode_num: u32,
+        mode: u32,
+        offset: usize,
+        len: usize,
+    ) -> Result<(), KernelError> {
+        use super::{FALLOC_FL_KEEP_SIZE, FALLOC_FL_PUNCH_HOLE};
+
+        let size = self
+            .inode_table
+            .get(inode_num as usize)
+            .ok_or(KernelError::FsError(FsError::NotFound))?
+            .size as usize;
+        let end = offset
+            .checked_add(len)
+            .filter(|&end| end as u64 <= self.format.max_file_size())
+            .ok_or(KernelError::FsError(FsError::FileTooLarge))?;
+
+        if mode & FALLOC_FL_PUNCH_HOLE != 0 {
+            let end = end.min(size);
+            let mut pos = offset;
+            while pos < end {
+                let block_start = pos - pos % BLOCK_SIZE;
+                let chunk_end = (block_start + BLOCK_SIZE).min(end);
+                if pos == block_start && chunk_end == block_start + BLOCK_SIZE {
+                    self.unmap_block(inode_num, pos / BLOCK_SIZE);
+                } else {
+                    self.zero_in_block(inode_num, pos, chunk_end - pos);
+                }
+                pos = chunk_end;
+            }
+        } else {
+            if end.div_ceil(BLOCK_SIZE) > DOUBLE_INDIRECT_MAX_BLOCKS {
+                return Err(KernelError::FsError(FsError::FileTooLarge));
+            }
+            for logical_block in offset / BLOCK_SIZE..end.div_ceil(BLOCK_SIZE) {
+                self.ensure_block(inode_num, logical_block)
+                    .map_err(|_| KernelError::FsError(FsError::NoSpace))?;
+            }
+            if mode & FALLOC_FL_KEEP_SIZE == 0 && end > size {
+                self.inode_table[inode_num as usize].size = end as u64;
+            }
+        }
+
+        self.inode_table[inode_num as usize].mtime = now_ns();
         Ok(())
     }
 
@@ -1609,9 +1938,12 @@ impl BlockFsInner {
                 .ok_or(KernelError::FsError(FsError::NotFound))?;
             inode.size as usize
         };
+        if size as u64 > self.format.max_file_size() {
+            return Err(KernelError::FsError(FsError::FileTooLarge));
+        }
 
         // Set the new size
-        self.inode_table[inode_num as usize].size = size as u32;
+        self.inode_table[inode_num as usize].size = size as u64;
 
         // Free data blocks that are fully beyond the new size
         if size < old_size {
@@ -1705,20 +2037,125 @@ impl BlockFsInner {
         let type_bits = inode.mode & 0xF000;
         let perm_bits = permissions_to_mode(permissions, false) & 0x0FFF;
         inode.mode = type_bits | perm_bits;
-        inode.ctime = crate::arch::timer::read_hw_timestamp() as u32;
+        inode.ctime = now_ns();
+
+        Ok(())
+    }
+
+    /// Change the owner and group of an inode.
+    ///
+    /// The on-disk inode stores 16-bit IDs; larger IDs are rejected.
+    fn chown_inode(&mut self, inode_num: u32, uid: u32, gid: u32) -> Result<(), KernelError> {
+        let uid = u16::try_from(uid).map_err(|_| KernelError::InvalidArgument {
+            name: "uid",
+            value: "exceeds 16-bit inode field",
+        })?;
+        let gid = u16::try_from(gid).map_err(|_| KernelError::InvalidArgument {
+            name: "gid",
+            value: "exceeds 16-bit inode field",
+        })?;
+        let inode = self
+            .inode_table
+            .get_mut(inode_num as usize)
+            .ok_or(KernelError::FsError(FsError::NotFound))?;
+
+        inode.uid = uid;
+        inode.gid = gid;
+        inode.ctime = now_ns();
+
+        Ok(())
+    }
+
+    /// Read all extended attributes of an inode from its xattr block.
+    fn read_xattrs(&self, inode_num: u32) -> Result<BTreeMap<String, Vec<u8>>, KernelError> {
+        let inode = self
+            .inode_table
+            .get(inode_num as usize)
+            .ok_or(KernelError::FsError(FsError::NotFound))?;
+        if inode.xattr_block == 0 {
+            return Ok(BTreeMap::new());
+        }
+        decode_xattr_block(self.block_ref(inode.xattr_block as usize))
+    }
+
+    /// Replace the extended attributes of an inode, allocating its xattr
+    /// block on first use and freeing it when the last attribute goes.
+    fn write_xattrs(
+        &mut self,
+        inode_num: u32,
+        attrs: &BTreeMap<String, Vec<u8>>,
+    ) -> Result<(), KernelError> {
+        let current = self
+            .inode_table
+            .get(inode_num as usize)
+            .ok_or(KernelError::FsError(FsError::NotFound))?
+            .xattr_block;
 
+        let block_num = if attrs.is_empty() {
+            if current != 0 {
+                self.free_block(current);
+            }
+            0
+        } else {
+            let encoded = encode_xattr_block(attrs)?;
+            let block_num = if current != 0 {
+                current
+            } else {
+                self.allocate_block()
+                    .ok_or(KernelError::FsError(FsError::NoSpace))?
+            };
+            self.materialize_block(block_num as usize);
+            self.block_data[block_num as usize].copy_from_slice(&encoded);
+            self.mark_dirty(block_num);
+            block_num
+        };
+
+        let inode = &mut self.inode_table[inode_num as usize];
+        inode.xattr_block = block_num;
+        inode.ctime = now_ns();
         Ok(())
     }
 
+    /// Create or replace one extended attribute, honouring `XATTR_CREATE` /
+    /// `XATTR_REPLACE`.
+    fn set_xattr_inode(
+        &mut self,
+        inode_num: u32,
+        name: &str,
+        value: &[u8],
+        flags: u32,
+    ) -> Result<(), KernelError> {
+        super::xattr::validate_name(name)?;
+        let mut attrs = self.read_xattrs(inode_num)?;
+        let exists = attrs.contains_key(name);
+        if flags & super::xattr::XATTR_CREATE != 0 && exists {
+            return Err(KernelError::FsError(FsError::AlreadyExists));
+        }
+        if flags & super::xattr::XATTR_REPLACE != 0 && !exists {
+            return Err(KernelError::FsError(FsError::NotFound));
+        }
+        attrs.insert(String::from(name), Vec::from(value));
+        self.write_xattrs(inode_num, &attrs)
+    }
+
+    /// Remove one extended attribute.
+    fn remove_xattr_inode(&mut self, inode_num: u32, name: &str) -> Result<(), KernelError> {
+        let mut attrs = self.read_xattrs(inode_num)?;
+        if attrs.remove(name).is_none() {
+            return Err(KernelError::FsError(FsError::NotFound));
+        }
+        self.write_xattrs(inode_num, &attrs)
+    }
+
     /// Create a hard link in a directory.
     ///
-    /// Adds a new directory entry `name` in `dir_inode` pointing to the
-    /// same inode as `target`. The target's link count is incremented.
+    /// Adds a new directory entry `name` in `dir_inode` pointing to
+    /// `target_inode`, whose link count is incremented.
     fn link_in_dir(
         &mut self,
         dir_inode: u32,
         name: &str,
-        target: Arc<dyn VfsNode>,
+        target_inode: u32,
     ) -> Result<(), KernelError> {
         if name.is_empty() || name.len() > MAX_FILENAME_LEN {
             return Err(KernelError::InvalidArgument {
@@ -1727,8 +2164,14 @@ impl BlockFsInner {
             });
         }
 
+        let target = *self
+            .inode_table
+            .get(target_inode as usize)
+            .filter(|inode| inode.links_count > 0)
+            .ok_or(KernelError::FsError(FsError::NotFound))?;
+
         // Hard links to directories are not allowed (POSIX)
-        if target.node_type() == NodeType::Directory {
+        if target.is_dir() {
             return Err(KernelError::FsError(FsError::IsADirectory));
         }
 
@@ -1737,19 +2180,8 @@ impl BlockFsInner {
             return Err(KernelError::FsError(FsError::AlreadyExists));
         }
 
-        // Get the target's inode number from its metadata
-        let target_meta = target.metadata()?;
-        let target_inode = target_meta.inode as u32;
-
-        // Verify the target inode exists in our inode table (same filesystem)
-        if target_inode as usize >= self.inode_table.len()
-            || self.inode_table[target_inode as usize].links_count == 0
-        {
-            return Err(KernelError::FsError(FsError::NotSupported));
-        }
-
         // Determine file type for directory entry
-        let file_type = if self.inode_table[target_inode as usize].is_symlink() {
+        let file_type = if target.is_symlink() {
             DiskDirEntry::FT_SYMLINK
         } else {
             DiskDirEntry::FT_REG_FILE
@@ -1934,29 +2366,17 @@ impl BlockFsInner {
     ///
     /// Parses the fixed header fields and name bytes from raw block data.
     fn read_dir_entry(&self, block: &[u8], offset: usize) -> DiskDirEntry {
-        let inode = u32::from_le_bytes([
-            block[offset],
-            block[offset + 1],
-            block[offset + 2],
-            block[offset + 3],
-        ]);
-        let rec_len = u16::from_le_bytes([block[offset + 4], block[offset + 5]]);
-        let name_len = block[offset + 6];
-        let file_type = block[offset + 7];
+        let header = DirEntryHeader::decode(block, offset).unwrap_or_default();
 
         let mut name = [0u8; 255];
-        let actual_name_len = (name_len as usize).min(MAX_FILENAME_LEN);
-        let available = block.len() - (offset + DIR_ENTRY_HEADER_SIZE);
-        let copy_len = actual_name_len.min(available);
-        name[..copy_len].copy_from_slice(
-            &block[offset + DIR_ENTRY_HEADER_SIZE..offset + DIR_ENTRY_HEADER_SIZE + copy_len],
-        );
+        let name_bytes = header.name(block, offset);
+        name[..name_bytes.len()].copy_from_slice(name_bytes);
 
         DiskDirEntry {
-            inode,
-            rec_len,
-            name_len,
-            file_type,
+            inode: header.inode,
+            rec_len: header.rec_len,
+            name_len: header.name_len,
+            file_type: header.file_type,
             name,
         }
     }
@@ -2020,7 +2440,7 @@ impl BlockFsInner {
         file_type: u8,
     ) -> Result<(), KernelError> {
         let entry = DiskDirEntry::new(target_inode, name, file_type);
-        let entry_size = align4(DIR_ENTRY_HEADER_SIZE + entry.name_len as usize);
+        let entry_size = dir_entry_len(entry.name_len as usize);
 
         let dir_size = self.inode_table[dir_inode as usize].size as usize;
 
@@ -2054,12 +2474,12 @@ impl BlockFsInner {
             }
 
             // Write at the start of the new block
-            self.serialize_dir_entry(dir_inode, next_block_idx, 0, &entry, entry_size)?;
+            self.serialize_dir_entry(dir_inode, next_block_idx, 0, &entry)?;
 
             // Update directory size to include any padding in the old block plus the new
             // entry
             let new_size = (next_block_idx * BLOCK_SIZE) + entry_size;
-            self.inode_table[dir_inode as usize].size = new_size as u32;
+            self.inode_table[dir_inode as usize].size = new_size as u64;
         } else {
             // Allocate the first block if needed (empty directory)
             if self.inode_table[dir_inode as usize].direct_blocks[block_idx] == 0 {
@@ -2070,11 +2490,11 @@ impl BlockFsInner {
                 self.inode_table[dir_inode as usize].blocks += 1;
             }
 
-            self.serialize_dir_entry(dir_inode, block_idx, offset_in_block, &entry, entry_size)?;
+            self.serialize_dir_entry(dir_inode, block_idx, offset_in_block, &entry)?;
 
             // Update directory size
             let new_size = dir_size + entry_size;
-            self.inode_table[dir_inode as usize].size = new_size as u32;
+            self.inode_table[dir_inode as usize].size = new_size as u64;
         }
 
         Ok(())
@@ -2087,7 +2507,6 @@ impl BlockFsInner {
         block_idx: usize,
         offset: usize,
         entry: &DiskDirEntry,
-        entry_size: usize,
     ) -> Result<(), KernelError> {
         let block_num = self.inode_table[dir_inode as usize].direct_blocks[block_idx];
         if block_num == 0 {
@@ -2097,39 +2516,23 @@ impl BlockFsInner {
         self.materialize_block(block_num as usize);
         let block = &mut self.block_data[block_num as usize];
 
-        // Write inode (4 bytes, little-endian)
-        let inode_bytes = entry.inode.to_le_bytes();
-        block[offset..offset + 4].copy_from_slice(&inode_bytes);
-
-        // Write rec_len (2 bytes, little-endian) - use the padded entry_size
-        let rec_len_bytes = (entry_size as u16).to_le_bytes();
-        block[offset + 4..offset + 6].copy_from_slice(&rec_len_bytes);
-
-        // Write name_len (1 byte)
-        block[offset + 6] = entry.name_len;
-
-        // Write file_type (1 byte)
-        block[offset + 7] = entry.file_type;
-
-        // Write name bytes
-        let name_len = entry.name_len as usize;
-        block[offset + DIR_ENTRY_HEADER_SIZE..offset + DIR_ENTRY_HEADER_SIZE + name_len]
-            .copy_from_slice(&entry.name[..name_len]);
-
-        // Zero-fill any padding bytes between name end and rec_len boundary
-        let name_end = offset + DIR_ENTRY_HEADER_SIZE + name_len;
-        let rec_end = offset + entry_size;
-        for byte in &mut block[name_end..rec_end] {
-            *byte = 0;
-        }
+        let name = &entry.name[..entry.name_len as usize];
+        encode_dir_entry(block, offset, entry.inode, name, entry.file_type);
 
         self.mark_dirty(block_num);
 
         Ok(())
     }
 
-    /// Free all data blocks belonging to an inode (direct + indirect).
+    /// Free all data blocks belonging to an inode (direct + indirect) and its
+    /// extended attribute block.
     fn free_inode_blocks(&mut self, inode_num: u32) {
+        let xattr_block = self.inode_table[inode_num as usize].xattr_block;
+        if xattr_block != 0 {
+            self.free_block(xattr_block);
+            self.inode_table[inode_num as usize].xattr_block = 0;
+        }
+
         // Free direct blocks
         for i in 0..DIRECT_BLOCKS {
             let block_num = self.inode_table[inode_num as usize].direct_blocks[i];
@@ -2189,6 +2592,30 @@ fn permissions_to_mode(perms: Permissions, is_dir: bool) -> u16 {
     mode
 }
 
+/// Decode an xattr block into a name -> value map.
+fn decode_xattr_block(block: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, KernelError> {
+    let mut attrs = BTreeMap::new();
+    for entry in blockfs_format::decode_xattr_block(block).map_err(format_error)? {
+        let (name, value) = entry.map_err(format_error)?;
+        let name =
+            core::str::from_utf8(name).map_err(|_| KernelError::FsError(FsError::CorruptedData))?;
+        attrs.insert(String::from(name), value.to_vec());
+    }
+    Ok(attrs)
+}
+
+/// Encode `attrs` as a full xattr block (see [`decode_xattr_block`]).
+///
+/// Fails with `NoSpace` if the attributes do not fit in one block.
+fn encode_xattr_block(attrs: &BTreeMap<String, Vec<u8>>) -> Result<Vec<u8>, KernelError> {
+    let mut block = vec![0u8; BLOCK_SIZE];
+    let entries = attrs
+        .iter()
+        .map(|(name, value)| (name.as_bytes(), value.as_slice()));
+    blockfs_format::encode_xattr_block(entries, &mut block).map_err(format_error)?;
+    Ok(block)
+}
+
 /// A shared zero block for reads of unmaterialized (sparse) blocks.
 /// Avoids allocating 4KB for every unoccupied block index.
 static ZERO_BLOCK: [u8; BLOCK_SIZE] = [0u8; BLOCK_SIZE];
@@ -2315,7 +2742,7 @@ pub fn attach_virtio_backend(fs: &BlockFs, load_from_disk: bool) -> bool {
         return false;
     }
 
-    let backend = Arc::new(Mutex::new(VirtioBlockBackend));
+    let backend = Arc::new(Mutex::new(VirtioBlockBackend::whole_disk()));
     match fs.set_disk_backend(backend, load_from_disk) {
         Ok(()) => {
             crate::println!("[BLOCKFS] Attached virtio-blk disk backend for persistence");
@@ -2358,4 +2785,320 @@ mod tests {
         assert_eq!(fs.name(), "blockfs");
         assert!(!fs.is_readonly());
     }
+
+    #[test]
+    fn test_sparse_write_and_seek() {
+        let fs = BlockFs::format(1000, 100).unwrap();
+        let file = fs.root().create("sparse", Permissions::default()).unwrap();
+
+        // Data only in logical block 3; blocks 0-2 are a hole.
+        file.write(3 * BLOCK_SIZE, b"tail").unwrap();
+        let mut buf = [0xFFu8; 16];
+        file.read(BLOCK_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, [0u8; 16]);
+
+        assert_eq!(file.seek_data(0).unwrap(), Some(3 * BLOCK_SIZE));
+        assert_eq!(file.seek_hole(0).unwrap(), Some(0));
+        assert_eq!(
+            file.seek_hole(3 * BLOCK_SIZE).unwrap(),
+            Some(3 * BLOCK_SIZE + 4)
+        );
+        assert_eq!(file.seek_data(3 * BLOCK_SIZE + 4).unwrap(), None);
+    }
+
+    #[test]
+    fn test_fallocate_and_punch_hole() {
+        use crate::fs::{FALLOC_FL_KEEP_SIZE, FALLOC_FL_PUNCH_HOLE};
+
+        let fs = BlockFs::format(1000, 100).unwrap();
+        let file = fs
+            .root()
+            .create("prealloc", Permissions::default())
+            .unwrap();
+
+        file.fallocate(0, 0, 4 * BLOCK_SIZE).unwrap();
+        assert_eq!(file.metadata().unwrap().size, 4 * BLOCK_SIZE);
+        assert_eq!(file.seek_hole(0).unwrap(), Some(4 * BLOCK_SIZE));
+
+        file.write(0, &[0xAB; 4 * BLOCK_SIZE]).unwrap();
+        file.fallocate(
+            FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE,
+            BLOCK_SIZE / 2,
+            2 * BLOCK_SIZE,
+        )
+        .unwrap();
+
+        // Size unchanged; block 1 is now a hole, blocks 0 and 2 partly zeroed.
+        assert_eq!(file.metadata().unwrap().size, 4 * BLOCK_SIZE);
+        assert_eq!(file.seek_hole(0).unwrap(), Some(BLOCK_SIZE));
+        assert_eq!(file.seek_data(BLOCK_SIZE).unwrap(), Some(2 * BLOCK_SIZE));
+        let mut buf = [0u8; 4];
+        file.read(BLOCK_SIZE / 2 - 2, &mut buf).unwrap();
+        assert_eq!(buf, [0xAB, 0xAB, 0, 0]);
+        file.read(2 * BLOCK_SIZE + BLOCK_SIZE / 2 - 2, &mut buf)
+            .unwrap();
+        assert_eq!(buf, [0, 0, 0xAB, 0xAB]);
+
+        // A write into the hole sees zeros around it, not stale data.
+        file.write(BLOCK_SIZE + 8, b"x").unwrap();
+        file.read(BLOCK_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_hard_link_counts() {
+        let fs = BlockFs::format(1000, 100).unwrap();
+        let root = fs.root();
+        let file = root.create("a", Permissions::default()).unwrap();
+        file.write(0, b"shared").unwrap();
+
+        root.link("b", file.clone()).unwrap();
+        assert_eq!(file.link_count(), 2);
+        assert!(root.link("b", file.clone()).is_err());
+        let dir = root.mkdir("d", Permissions::default()).unwrap();
+        assert!(root.link("d2", dir).is_err());
+
+        root.unlink("a").unwrap();
+        let mut buf = [0u8; 6];
+        root.lookup("b").unwrap().read(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"shared");
+        assert_eq!(file.link_count(), 1);
+    }
+
+    #[test]
+    fn test_rename_replaces_target() {
+        let fs = BlockFs::format(1000, 100).unwrap();
+        let root = fs.root();
+        root.create("old", Permissions::default())
+            .unwrap()
+            .write(0, b"new contents")
+            .unwrap();
+        let replaced = root.create("target", Permissions::default()).unwrap();
+        replaced.write(0, b"stale").unwrap();
+
+        root.rename("old", &*root, "target").unwrap();
+        assert!(root.lookup("old").is_err());
+        let mut buf = [0u8; 12];
+        root.lookup("target").unwrap().read(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"new contents");
+        assert_eq!(replaced.link_count(), 0);
+
+        // Renaming onto another link to the same inode is a no-op.
+        root.link("alias", root.lookup("target").unwrap()).unwrap();
+        root.rename("alias", &*root, "target").unwrap();
+        assert!(root.lookup("alias").is_ok());
+    }
+
+    #[test]
+    fn test_rename_directory_across_parents() {
+        let fs = BlockFs::format(1000, 100).unwrap();
+        let root = fs.root();
+        let src = root.mkdir("src", Permissions::default()).unwrap();
+        let dst = root.mkdir("dst", Permissions::default()).unwrap();
+        let moved = src.mkdir("sub", Permissions::default()).unwrap();
+        moved.create("f", Permissions::default()).unwrap();
+
+        src.rename("sub", &*dst, "sub").unwrap();
+        assert!(src.lookup("sub").is_err());
+        assert!(dst.lookup("sub").unwrap().lookup("f").is_ok());
+        assert_eq!(src.link_count(), 2);
+        assert_eq!(dst.link_count(), 3);
+        let parent = dst.lookup("sub").unwrap().lookup("..").unwrap();
+        assert_eq!(
+            parent.metadata().unwrap().inode,
+            dst.metadata().unwrap().inode
+        );
+
+        // A directory cannot move beneath itself or replace a file, and a
+        // non-empty directory cannot be replaced.
+        let sub = dst.lookup("sub").unwrap();
+        assert!(dst.rename("sub", &*sub, "loop").is_err());
+        root.create("file", Permissions::default()).unwrap();
+        assert!(root.rename("dst", &*root, "file").is_err());
+        assert!(root.rename("src", &*root, "dst").is_err());
+        root.rename("dst", &*root, "src").unwrap();
+        assert!(root.lookup("src").unwrap().lookup("sub").is_ok());
+    }
+
+    #[test]
+    fn test_rename_across_filesystems_fails() {
+        let a = BlockFs::format(1000, 100).unwrap();
+        let b = BlockFs::format(1000, 100).unwrap();
+        a.root().create("f", Permissions::default()).unwrap();
+        assert_eq!(
+            a.root().rename("f", &*b.root(), "f"),
+            Err(KernelError::FsError(FsError::CrossDevice))
+        );
+    }
+
+    #[test]
+    fn test_xattr_block_round_trip() {
+        let mut attrs = BTreeMap::new();
+        attrs.insert(String::from("user.a"), vec![1, 2, 3]);
+        attrs.insert(String::from("security.capability"), vec![0xAA; 20]);
+        let block = encode_xattr_block(&attrs).unwrap();
+        assert_eq!(decode_xattr_block(&block).unwrap(), attrs);
+        assert!(decode_xattr_block(&ZERO_BLOCK).is_err());
+    }
+
+    #[test]
+    fn test_xattr_block_overflow() {
+        let mut attrs = BTreeMap::new();
+        attrs.insert(String::from("user.big"), vec![0; BLOCK_SIZE]);
+        assert!(encode_xattr_block(&attrs).is_err());
+    }
+
+    #[test]
+    fn test_node_xattrs_use_xattr_block() {
+        let fs = BlockFs::format(1000, 100).unwrap();
+        let file = fs
+            .root()
+            .create("labelled", Permissions::default())
+            .unwrap();
+
+        file.set_xattr("user.color", b"blue", 0).unwrap();
+        file.set_xattr("security.capability", b"cap", 0).unwrap();
+        assert_eq!(file.get_xattr("user.color").unwrap(), b"blue");
+        assert_eq!(
+            file.list_xattr().unwrap(),
+            vec![
+                String::from("security.capability"),
+                String::from("user.color")
+            ]
+        );
+        assert!(file
+            .set_xattr("user.color", b"red", crate::fs::xattr::XATTR_CREATE)
+            .is_err());
+
+        file.remove_xattr("user.color").unwrap();
+        file.remove_xattr("security.capability").unwrap();
+        assert!(file.get_xattr("user.color").is_err());
+        assert!(file.list_xattr().unwrap().is_empty());
+    }
+
+    /// A write as a [`MemDisk`] saw it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Io {
+        Write(u64),
+        Fua(u64),
+        Barrier,
+    }
+
+    /// RAM-backed disk for persistence tests.
+    struct MemDisk {
+        blocks: Mutex<Vec<Vec<u8>>>,
+        log: Mutex<Vec<Io>>,
+    }
+
+    impl MemDisk {
+        fn new(blocks: usize) -> Self {
+            Self {
+                blocks: Mutex::new(vec![vec![0u8; BLOCK_SIZE]; blocks]),
+                log: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl DiskBackend for MemDisk {
+        fn read_block(&self, block_num: u64, buf: &mut [u8]) -> Result<(), KernelError> {
+            buf[..BLOCK_SIZE].copy_from_slice(&self.blocks.lock()[block_num as usize]);
+            Ok(())
+        }
+
+        fn write_block(&self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+            self.blocks.lock()[block_num as usize].copy_from_slice(&data[..BLOCK_SIZE]);
+            self.log.lock().push(Io::Write(block_num));
+            Ok(())
+        }
+
+        fn write_block_fua(&self, block_num: u64, data: &[u8]) -> Result<(), KernelError> {
+            self.blocks.lock()[block_num as usize].copy_from_slice(&data[..BLOCK_SIZE]);
+            self.log.lock().push(Io::Fua(block_num));
+            Ok(())
+        }
+
+        fn barrier(&self) -> Result<(), KernelError> {
+            self.log.lock().push(Io::Barrier);
+            Ok(())
+        }
+
+        fn block_count(&self) -> u64 {
+            self.blocks.lock().len() as u64
+        }
+
+        fn is_read_only(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_v2_sizes_persist() {
+        let disk: Arc<Mutex<dyn DiskBackend>> = Arc::new(Mutex::new(MemDisk::new(1000)));
+        let fs = BlockFs::format(1000, 100).unwrap();
+        fs.set_disk_backend(disk.clone(), false).unwrap();
+        let file = fs.root().create("big", Permissions::default()).unwrap();
+        file.write(0, b"head").unwrap();
+        file.truncate(5 << 30).unwrap();
+        fs.sync().unwrap();
+
+        let reopened = BlockFs::open_existing(disk.clone()).unwrap();
+        assert_eq!(reopened.inner.read().format, InodeFormat::V2);
+        let file = reopened.root().lookup("big").unwrap();
+        assert_eq!(file.metadata().unwrap().size, 5 << 30);
+        let mut buf = [0u8; 4];
+        file.read(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"head");
+
+        // An unknown incompatible feature makes the image unmountable.
+        let mut block = [0u8; BLOCK_SIZE];
+        disk.lock().read_block(0, &mut block).unwrap();
+        block[72] |= 0x80;
+        disk.lock().write_block(0, &block).unwrap();
+        assert!(matches!(
+            BlockFs::open_existing(disk),
+            Err(KernelError::FsError(FsError::NotSupported))
+        ));
+    }
+
+    #[test]
+    fn test_sync_write_ordering() {
+        let disk = Arc::new(Mutex::new(MemDisk::new(1000)));
+        let fs = BlockFs::format(1000, 100).unwrap();
+        fs.set_disk_backend(disk.clone(), false).unwrap();
+        let file = fs.root().create("f", Permissions::default()).unwrap();
+        file.write(0, b"data").unwrap();
+        fs.sync().unwrap();
+
+        let log = disk.lock().log.lock().clone();
+        let first_data = fs.inner.read().superblock.first_data_block as u64;
+        let barriers: Vec<usize> = (0..log.len()).filter(|&i| log[i] == Io::Barrier).collect();
+        assert_eq!(barriers.len(), 2);
+        // Data, barrier, bitmap and inode table, barrier, superblock
+        assert!(log[..barriers[0]]
+            .iter()
+            .all(|io| matches!(io, Io::Write(b) if *b >= first_data)));
+        assert!(log[barriers[0] + 1..barriers[1]]
+            .iter()
+            .all(|io| matches!(io, Io::Write(b) if *b > 0 && *b < first_data)));
+        assert_eq!(&log[barriers[1] + 1..], &[Io::Fua(0)]);
+    }
+
+    #[test]
+    fn test_v1_size_limit() {
+        let fs = BlockFs::format(1000, 100).unwrap();
+        fs.inner.write().format = InodeFormat::V1;
+        let file = fs.root().create("capped", Permissions::default()).unwrap();
+
+        file.truncate(u32::MAX as usize).unwrap();
+        assert!(matches!(
+            file.truncate(u32::MAX as usize + 1),
+            Err(KernelError::FsError(FsError::FileTooLarge))
+        ));
+        assert!(matches!(
+            file.write(u32::MAX as usize, b"x"),
+            Err(KernelError::FsError(FsError::FileTooLarge))
+        ));
+        // A write straddling the limit is cut short.
+        assert_eq!(file.write(u32::MAX as usize - 1, b"xy").unwrap(), 1);
+    }
 }
@@ -1,6 +1,9 @@
 //! Device Filesystem (/dev)
 //!
-//! Provides device nodes for hardware and virtual devices.
+//! Provides device nodes for hardware and virtual devices. Block devices,
+//! serial ports and virtio-console ports register their nodes at runtime
+//! through [`register_block_device`] and [`register_char_device`]; those
+//! appear in every devfs instance.
 
 use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 
@@ -12,6 +15,14 @@ use super::bare_lock::RwLock;
 use super::{DirEntry, Filesystem, Metadata, NodeType, Permissions, VfsNode};
 use crate::error::{FsError, KernelError};
 
+/// The serial port behind `/dev/<name>`.
+fn serial_port(name: &str) -> Result<Arc<crate::drivers::serial::SerialTty>, KernelError> {
+    crate::drivers::serial::get(name).ok_or(KernelError::NotFound {
+        resource: "serial port",
+        id: 0,
+    })
+}
+
 /// Device node
 struct DevNode {
     name: String,
@@ -32,7 +43,6 @@ impl DevNode {
         }
     }
 
-    #[allow(dead_code)] // Block device creation API -- used when block devices are registered
     fn new_block(name: String, major: u32, minor: u32) -> Self {
         Self {
             name,
@@ -56,261 +66,30 @@ impl VfsNode for DevNode {
                 // /dev/null always returns EOF
                 Ok(0)
             }
-            "zero" => {
-                // /dev/zero returns zeros
+            "zero" | "full" => {
+                // /dev/zero and /dev/full return zeros
                 buffer.fill(0);
                 Ok(buffer.len())
             }
             "random" | "urandom" => {
-                // Simple pseudo-random for now
-                for byte in buffer.iter_mut() {
-                    *byte = (crate::read_timestamp() & 0xFF) as u8;
-                }
+                // Both nodes read from the kernel CSPRNG, which is seeded
+                // before userspace starts and so never needs to block.
+                crate::crypto::random::get_random()
+                    .fill_bytes(buffer)
+                    .map_err(|_| KernelError::HardwareError {
+                        device: "urandom",
+                        code: 0,
+                    })?;
                 Ok(buffer.len())
             }
             "console" | "tty0" => {
-                // Read from serial console, respecting terminal state (ICANON, ECHO).
-                //
-                // Canonical mode (ICANON set, default): buffer until newline/EOF,
-                // support VERASE (backspace), return full line.
-                // Raw mode (ICANON cleared): return characters immediately per
-                // VMIN/VTIME settings.
-                #[cfg(target_arch = "x86_64")]
-                {
-                    use crate::drivers::terminal;
-
-                    let canonical = terminal::is_canonical_mode();
-                    let echo = terminal::is_echo_enabled();
-                    let verase = terminal::get_verase();
-                    let vmin = terminal::get_vmin() as usize;
-
-                    if canonical {
-                        // Canonical mode: line-buffered with editing support.
-                        // Buffer characters until newline, CR, or EOF (Ctrl-D).
-                        // Support backspace (VERASE) to erase last character.
-                        //
-                        // Block indefinitely waiting for input, just like a real
-                        // terminal device. POSIX requires read() on a terminal to
-                        // block until data is available. Without this, shells and
-                        // other interactive programs see EOF and exit immediately.
-                        let mut line_buf: [u8; 4096] = [0u8; 4096];
-                        let mut line_len: usize = 0;
-
-                        loop {
-                            let byte = loop {
-                                let lsr: u8;
-                                // SAFETY: Reading COM1 LSR (port 0x3FD).
-                                unsafe {
-                                    core::arch::asm!(
-                                        "in al, dx",
-                                        out("al") lsr,
-                                        in("dx") 0x3FDu16,
-                                        options(nomem, nostack)
-                                    );
-                                }
-                                if lsr & 1 != 0 {
-                                    let data: u8;
-                                    // SAFETY: Reading COM1 data register (port 0x3F8).
-                                    unsafe {
-                                        core::arch::asm!(
-                                            "in al, dx",
-                                            out("al") data,
-                                            in("dx") 0x3F8u16,
-                                            options(nomem, nostack)
-                                        );
-                                    }
-                                    break data;
-                                }
-                                core::hint::spin_loop();
-                            };
-
-                            // Handle special characters in canonical mode
-                            if byte == verase || byte == 8 {
-                                // Backspace: erase last character
-                                if line_len > 0 {
-                                    line_len -= 1;
-                                    if echo {
-                                        // Echo backspace-space-backspace
-                                        for &b in &[8u8, b' ', 8u8] {
-                                            // SAFETY: Writing COM1 data register.
-                                            unsafe {
-                                                while {
-                                                    let s: u8;
-                                                    core::arch::asm!(
-                                                        "in al, dx",
-                                                        out("al") s,
-                                                        in("dx") 0x3FDu16,
-                                                        options(nomem, nostack)
-                                                    );
-                                                    s & 0x20 == 0
-                                                } {
-                                                    core::hint::spin_loop();
-                                                }
-                                                core::arch::asm!(
-                                                    "out dx, al",
-                                                    in("al") b,
-                                                    in("dx") 0x3F8u16,
-                                                    options(nomem, nostack)
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
-                                continue;
-                            }
-
-                            // CR -> NL conversion (c_iflag ICRNL)
-                            let byte = if byte == b'\r' { b'\n' } else { byte };
-
-                            // Store in line buffer
-                            if line_len < line_buf.len() {
-                                line_buf[line_len] = byte;
-                                line_len += 1;
-                            }
-
-                            // Echo the character
-                            if echo {
-                                let echo_byte = byte;
-                                // SAFETY: Writing COM1 data register.
-                                unsafe {
-                                    while {
-                                        let s: u8;
-                                        core::arch::asm!(
-                                            "in al, dx",
-                                            out("al") s,
-                                            in("dx") 0x3FDu16,
-                                            options(nomem, nostack)
-                                        );
-                                        s & 0x20 == 0
-                                    } {
-                                        core::hint::spin_loop();
-                                    }
-                                    core::arch::asm!(
-                                        "out dx, al",
-                                        in("al") echo_byte,
-                                        in("dx") 0x3F8u16,
-                                        options(nomem, nostack)
-                                    );
-                                    // Also echo CR after NL for terminal display
-                                    if echo_byte == b'\n' {
-                                        while {
-                                            let s: u8;
-                                            core::arch::asm!(
-                                                "in al, dx",
-                                                out("al") s,
-                                                in("dx") 0x3FDu16,
-                                                options(nomem, nostack)
-                                            );
-                                            s & 0x20 == 0
-                                        } {
-                                            core::hint::spin_loop();
-                                        }
-                                        core::arch::asm!(
-                                            "out dx, al",
-                                            in("al") b'\r',
-                                            in("dx") 0x3F8u16,
-                                            options(nomem, nostack)
-                                        );
-                                    }
-                                }
-                            }
-
-                            // Line complete on newline or EOF
-                            if byte == b'\n' || byte == 4 {
-                                // Ctrl-D (EOF)
-                                let copy_len = line_len.min(buffer.len());
-                                buffer[..copy_len].copy_from_slice(&line_buf[..copy_len]);
-                                return Ok(copy_len);
-                            }
-                        }
-                    } else {
-                        // Raw mode (non-canonical): return characters immediately.
-                        // VMIN controls minimum chars, VTIME controls timeout.
-                        let target = if vmin == 0 { 1 } else { vmin.min(buffer.len()) };
-                        let max_spins: u64 = if terminal::get_vtime() > 0 {
-                            // VTIME is in tenths of a second; approximate with spin count
-                            (terminal::get_vtime() as u64) * 10_000_000
-                        } else if vmin == 0 {
-                            // VMIN=0, VTIME=0: pure non-blocking
-                            1
-                        } else {
-                            // VMIN>0, VTIME=0: block indefinitely
-                            u64::MAX
-                        };
-
-                        let mut bytes_read = 0usize;
-                        let mut total_spins: u64 = 0;
-
-                        while bytes_read < target {
-                            let lsr: u8;
-                            // SAFETY: Reading COM1 LSR (port 0x3FD).
-                            unsafe {
-                                core::arch::asm!(
-                                    "in al, dx",
-                                    out("al") lsr,
-                                    in("dx") 0x3FDu16,
-                                    options(nomem, nostack)
-                                );
-                            }
-                            if lsr & 1 != 0 {
-                                let data: u8;
-                                // SAFETY: Reading COM1 data register (port 0x3F8).
-                                unsafe {
-                                    core::arch::asm!(
-                                        "in al, dx",
-                                        out("al") data,
-                                        in("dx") 0x3F8u16,
-                                        options(nomem, nostack)
-                                    );
-                                }
-                                buffer[bytes_read] = data;
-                                bytes_read += 1;
-
-                                // Echo if enabled (even in raw mode)
-                                if echo {
-                                    // SAFETY: Writing COM1 data register.
-                                    unsafe {
-                                        while {
-                                            let s: u8;
-                                            core::arch::asm!(
-                                                "in al, dx",
-                                                out("al") s,
-                                                in("dx") 0x3FDu16,
-                                                options(nomem, nostack)
-                                            );
-                                            s & 0x20 == 0
-                                        } {
-                                            core::hint::spin_loop();
-                                        }
-                                        core::arch::asm!(
-                                            "out dx, al",
-                                            in("al") data,
-                                            in("dx") 0x3F8u16,
-                                            options(nomem, nostack)
-                                        );
-                                    }
-                                }
-
-                                // Reset timeout after each character
-                                total_spins = 0;
-                            } else {
-                                total_spins += 1;
-                                if total_spins >= max_spins {
-                                    break;
-                                }
-                                core::hint::spin_loop();
-                            }
-                        }
-
-                        Ok(bytes_read)
-                    }
-                }
-                // Non-x86_64: no serial port polling available, return EOF
-                #[cfg(not(target_arch = "x86_64"))]
-                {
-                    let _ = buffer;
-                    Ok(0)
-                }
+                // The console is ttyS0; its line discipline applies the
+                // terminal state (ICANON, ECHO, VMIN/VTIME).
+                Ok(crate::drivers::serial::read_console(buffer))
+            }
+            name if name.starts_with("ttyS") => Ok(serial_port(name)?.read(buffer)),
+            name if crate::drivers::virtio::console::is_port_node(name) => {
+                crate::drivers::virtio::console::read(name, buffer)
             }
             // evdev input device nodes: /dev/input/event*
             "input/event0" | "input/event1" => {
@@ -353,17 +132,38 @@ impl VfsNode for DevNode {
 
     fn write(&self, _offset: usize, data: &[u8]) -> Result<usize, KernelError> {
         match self.name.as_str() {
-            "null" => {
-                // /dev/null discards all data
+            "null" | "zero" => {
+                // /dev/null and /dev/zero discard all data
+                Ok(data.len())
+            }
+            "full" => {
+                // /dev/full is always out of space
+                Err(KernelError::FsError(FsError::NoSpace))
+            }
+            "random" | "urandom" => {
+                // Written data (e.g. a saved seed file) is mixed into the
+                // CSPRNG; it can only add entropy, never reduce it.
+                crate::crypto::random::add_entropy(data);
                 Ok(data.len())
             }
             "console" | "tty0" => {
-                // Write to console
-                for &_byte in data {
-                    crate::print!("{}", _byte as char);
+                // Userspace output: to ttyS0 through the console
+                // multiplexer, and to the framebuffer console.
+                crate::drivers::serial::write_console(data);
+                #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+                for &byte in data {
+                    crate::graphics::fbcon::_fbcon_print(format_args!("{}", byte as char));
+                    crate::print_capture::_capture_print(format_args!("{}", byte as char));
                 }
                 Ok(data.len())
             }
+            name if name.starts_with("ttyS") => {
+                serial_port(name)?.write(data);
+                Ok(data.len())
+            }
+            name if crate::drivers::virtio::console::is_port_node(name) => {
+                crate::drivers::virtio::console::write(name, data)
+            }
             _ => {
                 // Dispatch write to registered device driver via driver framework
                 if let Some(fw) = crate::services::driver_framework::try_get_driver_framework() {
@@ -388,6 +188,24 @@ impl VfsNode for DevNode {
         }
     }
 
+    /// Terminals and input devices are readable once input has arrived;
+    /// every other device never blocks.
+    fn poll_readiness(&self) -> u16 {
+        let readable = match self.name.as_str() {
+            "console" | "tty0" => crate::drivers::serial::console_input_ready(),
+            name if name.starts_with("ttyS") => {
+                serial_port(name).is_ok_and(|tty| tty.input_ready())
+            }
+            "input/event0" | "input/event1" => crate::drivers::evdev::has_events(self._minor),
+            _ => true,
+        };
+        if readable {
+            0x0001 | 0x0004 // POLLIN | POLLOUT
+        } else {
+            0x0004 // POLLOUT
+        }
+    }
+
     fn metadata(&self) -> Result<Metadata, KernelError> {
         Ok(Metadata {
             node_type: self.node_type,
@@ -584,6 +402,11 @@ impl DevRoot {
             Arc::new(DevNode::new_char(String::from("zero"), 1, 5)),
         );
 
+        devices.insert(
+            String::from("full"),
+            Arc::new(DevNode::new_char(String::from("full"), 1, 7)),
+        );
+
         devices.insert(
             String::from("random"),
             Arc::new(DevNode::new_char(String::from("random"), 1, 8)),
@@ -690,7 +513,7 @@ impl VfsNode for DevRoot {
             });
         }
 
-        for (name, device) in devices.iter() {
+        for (name, device) in devices.iter().chain(REGISTERED.read().iter()) {
             entries.push(DirEntry {
                 name: name.clone(),
                 node_type: device.node_type,
@@ -713,7 +536,9 @@ impl VfsNode for DevRoot {
         let devices = self.devices.read();
         devices
             .get(name)
-            .map(|node| node.clone() as Arc<dyn VfsNode>)
+            .cloned()
+            .or_else(|| REGISTERED.read().get(name).cloned())
+            .map(|node| node as Arc<dyn VfsNode>)
             .ok_or(KernelError::FsError(FsError::NotFound))
     }
 
@@ -748,6 +573,33 @@ impl VfsNode for DevRoot {
     }
 }
 
+/// Nodes registered at runtime, shared by every devfs instance.
+static REGISTERED: RwLock<BTreeMap<String, Arc<DevNode>>> = RwLock::new(BTreeMap::new());
+
+/// Add a block device node `/dev/<name>`. Reads and writes on it go to
+/// the driver bound to the driver framework device of the same name.
+pub fn register_block_device(name: &str, major: u32, minor: u32) {
+    REGISTERED.write().insert(
+        String::from(name),
+        Arc::new(DevNode::new_block(String::from(name), major, minor)),
+    );
+}
+
+/// Add a character device node `/dev/<name>` for a driver devfs knows by
+/// name, such as a serial port.
+pub fn register_char_device(name: &str, major: u32, minor: u32) {
+    REGISTERED.write().insert(
+        String::from(name),
+        Arc::new(DevNode::new_char(String::from(name), major, minor)),
+    );
+}
+
+/// Remove a node added by [`register_block_device`] or
+/// [`register_char_device`].
+pub fn unregister_device(name: &str) {
+    REGISTERED.write().remove(name);
+}
+
 /// Device filesystem
 pub struct DevFs {
     root: Arc<DevRoot>,
@@ -1,7 +1,7 @@
 //! File descriptors and file operations
 
 use alloc::{string::String, sync::Arc, vec::Vec};
-use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(not(target_arch = "aarch64"))]
 use spin::RwLock;
@@ -135,6 +135,9 @@ pub struct File {
     /// toggle this without mutable access.
     pub nonblock: AtomicBool,
 
+    /// Append mode, the other status flag F_SETFL may change after open.
+    pub append: AtomicBool,
+
     /// Current position in file
     pub position: RwLock<usize>,
 
@@ -150,10 +153,12 @@ impl File {
     /// Create a new file structure
     pub fn new(node: Arc<dyn VfsNode>, flags: OpenFlags) -> Self {
         let nb = flags.nonblock;
+        let append = flags.append;
         Self {
             node,
             flags,
             nonblock: AtomicBool::new(nb),
+            append: AtomicBool::new(append),
             position: RwLock::new(0),
             refcount: RwLock::new(1),
             path: None,
@@ -163,10 +168,12 @@ impl File {
     /// Create a new file structure with a known path
     pub fn new_with_path(node: Arc<dyn VfsNode>, flags: OpenFlags, path: String) -> Self {
         let nb = flags.nonblock;
+        let append = flags.append;
         Self {
             node,
             flags,
             nonblock: AtomicBool::new(nb),
+            append: AtomicBool::new(append),
             position: RwLock::new(0),
             refcount: RwLock::new(1),
             path: Some(path),
@@ -197,7 +204,7 @@ impl File {
 
         let mut pos = self.position.write();
 
-        if self.flags.append {
+        if self.append.load(Ordering::Relaxed) {
             // For append mode, always write at end
             let metadata = self.node.metadata()?;
             *pos = metadata.size;
@@ -280,12 +287,15 @@ pub struct FileEntry {
 }
 
 /// File descriptor table for a process
+///
+/// Slot `fd` of the table holds descriptor `fd`. New descriptors take the
+/// lowest free number (POSIX), and no descriptor at or above the caller's
+/// `RLIMIT_NOFILE` is ever handed out. Duplicates share one open file
+/// description (offset and status flags); the close-on-exec flag belongs to
+/// the descriptor and is never copied by dup.
 pub struct FileTable {
     /// File descriptors
     files: RwLock<Vec<Option<FileEntry>>>,
-
-    /// Next available file descriptor
-    next_fd: RwLock<FileDescriptor>,
 }
 
 impl FileTable {
@@ -300,7 +310,6 @@ impl FileTable {
 
         Self {
             files: RwLock::new(files),
-            next_fd: RwLock::new(3),
         }
     }
 }
@@ -311,6 +320,30 @@ impl Default for FileTable {
     }
 }
 
+/// Put `entry` in the lowest free slot at or above `min_fd` and below the
+/// descriptor limit. On failure the entry is handed back so the caller can
+/// drop its reference.
+fn install(
+    files: &mut Vec<Option<FileEntry>>,
+    min_fd: FileDescriptor,
+    entry: FileEntry,
+) -> Result<FileDescriptor, FileEntry> {
+    let max_fds = crate::process::rlimit::max_open_files();
+    let free = (min_fd..files.len().min(max_fds)).find(|&fd| files[fd].is_none());
+    let fd = match free {
+        Some(fd) => fd,
+        None => files.len().max(min_fd),
+    };
+    if fd >= max_fds {
+        return Err(entry);
+    }
+    if fd >= files.len() {
+        files.resize_with(fd + 1, || None);
+    }
+    files[fd] = Some(entry);
+    Ok(fd)
+}
+
 impl FileTable {
     /// Open a file and return a file descriptor
     pub fn open(&self, file: Arc<File>) -> Result<FileDescriptor, KernelError> {
@@ -324,27 +357,8 @@ impl FileTable {
         cloexec: bool,
     ) -> Result<FileDescriptor, KernelError> {
         let mut files = self.files.write();
-        let mut next_fd = self.next_fd.write();
-
-        let entry = FileEntry { file, cloexec };
-
-        // Find an empty slot
-        for (fd, slot) in files.iter_mut().enumerate() {
-            if slot.is_none() {
-                *slot = Some(entry);
-                return Ok(fd);
-            }
-        }
-
-        // No empty slot, append new one
-        let fd = *next_fd;
-        if fd >= 1024 {
-            return Err(KernelError::FsError(FsError::TooManyOpenFiles));
-        }
-
-        files.push(Some(entry));
-        *next_fd += 1;
-        Ok(fd)
+        install(&mut files, 0, FileEntry { file, cloexec })
+            .map_err(|_| KernelError::FsError(FsError::TooManyOpenFiles))
     }
 
     /// Get a file by descriptor
@@ -366,38 +380,25 @@ impl FileTable {
     pub fn close(&self, fd: FileDescriptor) -> Result<(), KernelError> {
         let mut files = self.files.write();
 
-        if fd >= files.len() {
-            return Err(KernelError::FsError(FsError::BadFileDescriptor));
-        }
-
-        if let Some(entry) = files[fd].take() {
-            // Decrement reference count
-            if entry.file.dec_ref() == 0 {
-                // Last reference, file will be dropped
+        match files.get_mut(fd).and_then(Option::take) {
+            Some(entry) => {
+                // Last reference drops the file with the entry
+                entry.file.dec_ref();
+                Ok(())
             }
-            Ok(())
-        } else {
-            Err(KernelError::FsError(FsError::BadFileDescriptor))
+            None => Err(KernelError::FsError(FsError::BadFileDescriptor)),
         }
     }
 
     /// Duplicate a file descriptor
     pub fn dup(&self, fd: FileDescriptor) -> Result<FileDescriptor, KernelError> {
-        let file = self
-            .get(fd)
-            .ok_or(KernelError::FsError(FsError::BadFileDescriptor))?;
-        file.inc_ref();
         // Duplicated FDs don't inherit close-on-exec
-        self.open(file)
+        self.dup_from(fd, 0, false)
     }
 
     /// Duplicate a file descriptor with close-on-exec flag
     pub fn dup_cloexec(&self, fd: FileDescriptor) -> Result<FileDescriptor, KernelError> {
-        let file = self
-            .get(fd)
-            .ok_or(KernelError::FsError(FsError::BadFileDescriptor))?;
-        file.inc_ref();
-        self.open_with_flags(file, true)
+        self.dup_from(fd, 0, true)
     }
 
     /// Duplicate fd to the lowest available fd >= min_fd (for F_DUPFD)
@@ -407,45 +408,35 @@ impl FileTable {
         min_fd: FileDescriptor,
         cloexec: bool,
     ) -> Result<FileDescriptor, KernelError> {
-        let file = self
+        if min_fd >= crate::process::rlimit::max_open_files() {
+            return Err(KernelError::InvalidArgument {
+                name: "min_fd",
+                value: "exceeds open file limit",
+            });
+        }
+        self.dup_from(fd, min_fd, cloexec)
+    }
+
+    /// Shared body of the dup family: copy `fd` into the lowest free slot
+    /// at or above `min_fd`.
+    fn dup_from(
+        &self,
+        fd: FileDescriptor,
+        min_fd: FileDescriptor,
+        cloexec: bool,
+    ) -> Result<FileDescriptor, KernelError> {
+        let mut files = self.files.write();
+        let file = files
             .get(fd)
+            .and_then(Option::as_ref)
+            .map(|entry| entry.file.clone())
             .ok_or(KernelError::FsError(FsError::BadFileDescriptor))?;
         file.inc_ref();
 
-        let mut files = self.files.write();
-        let mut next_fd = self.next_fd.write();
-
-        let entry = FileEntry { file, cloexec };
-
-        // Ensure the vector is large enough to scan from min_fd
-        while files.len() <= min_fd {
-            files.push(None);
-        }
-        if *next_fd <= min_fd {
-            *next_fd = min_fd;
-        }
-
-        // Find the lowest empty slot >= min_fd
-        for slot_fd in min_fd..files.len() {
-            if files[slot_fd].is_none() {
-                files[slot_fd] = Some(entry);
-                return Ok(slot_fd);
-            }
-        }
-
-        // No empty slot found in existing range; append new one
-        let new_fd = *next_fd;
-        if new_fd >= 1024 {
-            return Err(KernelError::FsError(FsError::TooManyOpenFiles));
-        }
-
-        // Ensure vector has capacity up to new_fd
-        while files.len() <= new_fd {
-            files.push(None);
-        }
-        files[new_fd] = Some(entry);
-        *next_fd = new_fd + 1;
-        Ok(new_fd)
+        install(&mut files, min_fd, FileEntry { file, cloexec }).map_err(|entry| {
+            entry.file.dec_ref();
+            KernelError::FsError(FsError::TooManyOpenFiles)
+        })
     }
 
     /// Replace a file descriptor with another
@@ -459,29 +450,8 @@ impl FileTable {
             return Ok(());
         }
 
-        let file = self
-            .get(old_fd)
-            .ok_or(KernelError::FsError(FsError::BadFileDescriptor))?;
-        file.inc_ref();
-
-        let mut files = self.files.write();
-
-        // Ensure files vector is large enough
-        while files.len() <= new_fd {
-            files.push(None);
-        }
-
-        // Close existing file at new_fd if any
-        if let Some(existing) = files[new_fd].take() {
-            existing.file.dec_ref();
-        }
-
-        // Set new file (dup2 doesn't preserve close-on-exec)
-        files[new_fd] = Some(FileEntry {
-            file,
-            cloexec: false,
-        });
-        Ok(())
+        // dup2 doesn't preserve close-on-exec
+        self.replace(old_fd, new_fd, false)
     }
 
     /// Replace a file descriptor with another, setting close-on-exec flag
@@ -499,25 +469,39 @@ impl FileTable {
             });
         }
 
-        let file = self
+        self.replace(old_fd, new_fd, cloexec)
+    }
+
+    /// Make `new_fd` refer to the file of `old_fd`, closing whatever
+    /// `new_fd` held. Both steps happen under one lock, so no other thread
+    /// can grab `new_fd` in between.
+    fn replace(
+        &self,
+        old_fd: FileDescriptor,
+        new_fd: FileDescriptor,
+        cloexec: bool,
+    ) -> Result<(), KernelError> {
+        if new_fd >= crate::process::rlimit::max_open_files() {
+            return Err(KernelError::FsError(FsError::BadFileDescriptor));
+        }
+
+        let mut files = self.files.write();
+        let file = files
             .get(old_fd)
+            .and_then(Option::as_ref)
+            .map(|entry| entry.file.clone())
             .ok_or(KernelError::FsError(FsError::BadFileDescriptor))?;
         file.inc_ref();
 
-        let mut files = self.files.write();
-
         // Ensure files vector is large enough
-        while files.len() <= new_fd {
-            files.push(None);
+        if new_fd >= files.len() {
+            files.resize_with(new_fd + 1, || None);
         }
 
         // Close existing file at new_fd if any
-        if let Some(existing) = files[new_fd].take() {
+        if let Some(existing) = files[new_fd].replace(FileEntry { file, cloexec }) {
             existing.file.dec_ref();
         }
-
-        // Set new file with specified close-on-exec flag
-        files[new_fd] = Some(FileEntry { file, cloexec });
         Ok(())
     }
 
@@ -525,15 +509,12 @@ impl FileTable {
     pub fn set_cloexec(&self, fd: FileDescriptor, cloexec: bool) -> Result<(), KernelError> {
         let mut files = self.files.write();
 
-        if fd >= files.len() {
-            return Err(KernelError::FsError(FsError::BadFileDescriptor));
-        }
-
-        if let Some(entry) = files[fd].as_mut() {
-            entry.cloexec = cloexec;
-            Ok(())
-        } else {
-            Err(KernelError::FsError(FsError::BadFileDescriptor))
+        match files.get_mut(fd).and_then(Option::as_mut) {
+            Some(entry) => {
+                entry.cloexec = cloexec;
+                Ok(())
+            }
+            None => Err(KernelError::FsError(FsError::BadFileDescriptor)),
         }
     }
 
@@ -541,15 +522,11 @@ impl FileTable {
     pub fn get_cloexec(&self, fd: FileDescriptor) -> Result<bool, KernelError> {
         let files = self.files.read();
 
-        if fd >= files.len() {
-            return Err(KernelError::FsError(FsError::BadFileDescriptor));
-        }
-
-        if let Some(entry) = files[fd].as_ref() {
-            Ok(entry.cloexec)
-        } else {
-            Err(KernelError::FsError(FsError::BadFileDescriptor))
-        }
+        files
+            .get(fd)
+            .and_then(Option::as_ref)
+            .map(|entry| entry.cloexec)
+            .ok_or(KernelError::FsError(FsError::BadFileDescriptor))
     }
 
     /// Close all file descriptors marked with close-on-exec
@@ -558,12 +535,9 @@ impl FileTable {
         let mut files = self.files.write();
 
         for slot in files.iter_mut() {
-            if let Some(entry) = slot.as_ref() {
-                if entry.cloexec {
-                    // Close this descriptor
-                    if let Some(entry) = slot.take() {
-                        entry.file.dec_ref();
-                    }
+            if slot.as_ref().is_some_and(|entry| entry.cloexec) {
+                if let Some(entry) = slot.take() {
+                    entry.file.dec_ref();
                 }
             }
         }
@@ -579,24 +553,22 @@ impl FileTable {
     /// All file descriptors are duplicated with same flags
     pub fn clone_for_fork(&self) -> Self {
         let files = self.files.read();
-        let next_fd = *self.next_fd.read();
-
-        let mut new_files = Vec::with_capacity(files.len());
-        for slot in files.iter() {
-            if let Some(entry) = slot {
-                entry.file.inc_ref();
-                new_files.push(Some(FileEntry {
-                    file: entry.file.clone(),
-                    cloexec: entry.cloexec,
-                }));
-            } else {
-                new_files.push(None);
-            }
-        }
+
+        let new_files = files
+            .iter()
+            .map(|slot| {
+                slot.as_ref().map(|entry| {
+                    entry.file.inc_ref();
+                    FileEntry {
+                        file: entry.file.clone(),
+                        cloexec: entry.cloexec,
+                    }
+                })
+            })
+            .collect();
 
         Self {
             files: RwLock::new(new_files),
-            next_fd: RwLock::new(next_fd),
         }
     }
 
@@ -611,3 +583,78 @@ impl FileTable {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::pipe::{create_pipe, PipeReadNode};
+
+    fn pipe_file() -> Arc<File> {
+        let (reader, _writer) = create_pipe().unwrap();
+        let node: Arc<dyn VfsNode> = Arc::new(PipeReadNode::new(reader));
+        Arc::new(File::new(node, OpenFlags::read_only()))
+    }
+
+    #[test]
+    fn test_lowest_free_descriptor() {
+        let table = FileTable::new();
+        assert_eq!(table.open(pipe_file()).unwrap(), 0);
+        assert_eq!(table.open(pipe_file()).unwrap(), 1);
+
+        // A high dup2 target must not move the next allocation past it
+        table.dup2(0, 10).unwrap();
+        assert_eq!(table.open(pipe_file()).unwrap(), 2);
+
+        table.close(1).unwrap();
+        assert_eq!(table.dup(0).unwrap(), 1);
+        assert_eq!(table.dup_at_least(0, 5, false).unwrap(), 5);
+        assert_eq!(table.dup_at_least(0, 5, false).unwrap(), 6);
+
+        assert!(table.close(42).is_err());
+        assert!(table.dup(42).is_err());
+    }
+
+    #[test]
+    fn test_dup_shares_description() {
+        let table = FileTable::new();
+        let file = pipe_file();
+        let fd = table.open(file.clone()).unwrap();
+        let dup = table.dup(fd).unwrap();
+
+        assert!(Arc::ptr_eq(
+            &table.get(fd).unwrap(),
+            &table.get(dup).unwrap()
+        ));
+        assert_eq!(*file.refcount.read(), 2);
+
+        // dup2 over an open descriptor drops the reference it held
+        let other = pipe_file();
+        let target = table.open(other.clone()).unwrap();
+        table.dup2(fd, target).unwrap();
+        assert_eq!(*other.refcount.read(), 0);
+        assert_eq!(*file.refcount.read(), 3);
+    }
+
+    #[test]
+    fn test_cloexec_is_per_descriptor() {
+        let table = FileTable::new();
+        let fd = table.open_with_flags(pipe_file(), true).unwrap();
+        let plain = table.dup(fd).unwrap();
+        let cloexec = table.dup_cloexec(fd).unwrap();
+        table.dup3(fd, 20, true).unwrap();
+        table.dup2(fd, 21).unwrap();
+
+        assert!(!table.get_cloexec(plain).unwrap());
+        assert!(table.get_cloexec(cloexec).unwrap());
+        assert!(table.get_cloexec(20).unwrap());
+        assert!(!table.get_cloexec(21).unwrap());
+        assert!(table.dup3(fd, fd, false).is_err());
+
+        table.close_on_exec();
+        assert!(table.get(fd).is_none());
+        assert!(table.get(cloexec).is_none());
+        assert!(table.get(20).is_none());
+        assert!(table.get(plain).is_some());
+        assert!(table.get(21).is_some());
+    }
+}
@@ -0,0 +1,328 @@
+//! Initramfs: boot-time root filesystem image
+//!
+//! The bootloader can load a "newc" cpio archive (as written by
+//! `cpio -o -H newc` or `bootimage-builder --initramfs`) alongside the
+//! kernel. When one is present, [`super::init`] mounts a tmpfs as the root
+//! filesystem and [`unpack`] extracts the archive into it, so early user
+//! space (init, devmgr, user-mode drivers) runs before any disk filesystem
+//! is mounted.
+//!
+//! Supported entry types are directories, regular files and symbolic links.
+//! Hard links (GNU cpio stores the data with the last name only) are
+//! materialized as copies, matching tmpfs hard-link semantics. Device
+//! nodes, FIFOs and sockets are skipped; `/dev` is provided by devfs.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{
+    error::{FsError, KernelError},
+    fs::{
+        get_vfs,
+        tar::{ensure_parent_dirs, split_path},
+        Permissions,
+    },
+};
+
+/// Magic of a newc header without checksum.
+const NEWC_MAGIC: &[u8; 6] = b"070701";
+/// Magic of a newc header with checksum (checksum is not verified).
+const NEWC_CRC_MAGIC: &[u8; 6] = b"070702";
+
+/// Size of a newc header: magic plus thirteen 8-digit hex fields.
+const HEADER_SIZE: usize = 110;
+
+/// Name of the entry that ends an archive.
+const TRAILER: &str = "TRAILER!!!";
+
+/// File type mask and types of `c_mode`.
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Field indices of the thirteen hex fields following the magic.
+mod field {
+    pub const INO: usize = 0;
+    pub const MODE: usize = 1;
+    pub const UID: usize = 2;
+    pub const GID: usize = 3;
+    pub const NLINK: usize = 4;
+    pub const FILESIZE: usize = 6;
+    pub const NAMESIZE: usize = 11;
+}
+
+/// One archive member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpioEntry<'a> {
+    /// Path relative to the archive root, without a leading `./` or `/`.
+    pub path: &'a str,
+    pub ino: u32,
+    /// File type and permission bits (`st_mode`).
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub nlink: u32,
+    /// File contents, or the target of a symbolic link.
+    pub data: &'a [u8],
+}
+
+fn corrupt() -> KernelError {
+    KernelError::FsError(FsError::CorruptedData)
+}
+
+/// Round `n` up to the 4-byte alignment used by newc.
+fn pad4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+/// Parse the hex field `index` of the header at `header`.
+fn hex_field(header: &[u8], index: usize) -> Result<u32, KernelError> {
+    let start = 6 + index * 8;
+    let digits = core::str::from_utf8(&header[start..start + 8]).map_err(|_| corrupt())?;
+    u32::from_str_radix(digits, 16).map_err(|_| corrupt())
+}
+
+/// Iterator over the members of a newc archive, created by [`entries`].
+pub struct CpioIter<'a> {
+    archive: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> CpioIter<'a> {
+    fn next_entry(&mut self) -> Result<Option<CpioEntry<'a>>, KernelError> {
+        let rest = &self.archive[self.offset..];
+        if rest.len() < HEADER_SIZE {
+            return Err(corrupt());
+        }
+        let header = &rest[..HEADER_SIZE];
+        if &header[..6] != NEWC_MAGIC && &header[..6] != NEWC_CRC_MAGIC {
+            return Err(corrupt());
+        }
+
+        let name_size = hex_field(header, field::NAMESIZE)? as usize;
+        let file_size = hex_field(header, field::FILESIZE)? as usize;
+        let data_start = pad4(HEADER_SIZE + name_size);
+        let data_end = data_start.checked_add(file_size).ok_or_else(corrupt)?;
+        if name_size == 0 || data_end > rest.len() {
+            return Err(corrupt());
+        }
+
+        // The name includes its NUL terminator.
+        let name = core::str::from_utf8(&rest[HEADER_SIZE..HEADER_SIZE + name_size - 1])
+            .map_err(|_| corrupt())?;
+        if name == TRAILER {
+            return Ok(None);
+        }
+        self.offset += pad4(data_end).min(rest.len());
+
+        let path = name.trim_start_matches("./").trim_start_matches('/');
+        Ok(Some(CpioEntry {
+            path: if path == "." { "" } else { path },
+            ino: hex_field(header, field::INO)?,
+            mode: hex_field(header, field::MODE)?,
+            uid: hex_field(header, field::UID)?,
+            gid: hex_field(header, field::GID)?,
+            nlink: hex_field(header, field::NLINK)?,
+            data: &rest[data_start..data_end],
+        }))
+    }
+}
+
+impl<'a> Iterator for CpioIter<'a> {
+    type Item = Result<CpioEntry<'a>, KernelError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let entry = self.next_entry();
+        if !matches!(entry, Ok(Some(_))) {
+            self.done = true;
+        }
+        entry.transpose()
+    }
+}
+
+/// Iterate over the members of a newc cpio archive.
+///
+/// Iteration stops at the trailer entry; a malformed header yields one
+/// `CorruptedData` error and ends the iteration.
+pub fn entries(archive: &[u8]) -> CpioIter<'_> {
+    CpioIter {
+        archive,
+        offset: 0,
+        done: archive.is_empty(),
+    }
+}
+
+/// The initramfs loaded by the bootloader, if any.
+///
+/// Only the x86_64 UEFI boot path can currently carry a ramdisk; other
+/// architectures have no boot protocol support for one yet.
+pub fn boot_image() -> Option<&'static [u8]> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        crate::arch::x86_64::boot::ramdisk()
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        None
+    }
+}
+
+/// Unpack a newc cpio archive into the VFS root.
+///
+/// Existing files are replaced and existing directories kept. Returns the
+/// number of entries created.
+pub fn unpack(archive: &[u8]) -> Result<usize, KernelError> {
+    let mut count = 0;
+    // Paths already created for multiply-linked inodes, so that the data
+    // stored with the last link reaches every name.
+    let mut links: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+
+    for entry in entries(archive) {
+        let entry = entry?;
+        if entry.path.is_empty() {
+            continue;
+        }
+
+        let mut path = String::from("/");
+        path.push_str(entry.path);
+        let (parent_path, name) = split_path(&path)?;
+        if parent_path != "/" {
+            ensure_parent_dirs(parent_path)?;
+        }
+
+        let vfs = get_vfs().read();
+        let parent = vfs.resolve_path(parent_path)?;
+        let permissions = Permissions::from_mode(entry.mode & 0o7777);
+
+        let node = match entry.mode & S_IFMT {
+            S_IFDIR => match parent.lookup(name) {
+                Ok(dir) => {
+                    dir.chmod(permissions)?;
+                    dir
+                }
+                Err(_) => parent.mkdir(name, permissions)?,
+            },
+            S_IFREG => {
+                let _ = parent.unlink(name);
+                let file = parent.create(name, permissions)?;
+                if !entry.data.is_empty() {
+                    file.write(0, entry.data)?;
+                }
+                if entry.nlink > 1 {
+                    let names = links.entry(entry.ino).or_default();
+                    if !entry.data.is_empty() {
+                        for other in names.iter() {
+                            let other = vfs.resolve_path(other)?;
+                            other.truncate(0)?;
+                            other.write(0, entry.data)?;
+                        }
+                    }
+                    names.push(path.clone());
+                }
+                file
+            }
+            S_IFLNK => {
+                let target = core::str::from_utf8(entry.data).map_err(|_| corrupt())?;
+                let _ = parent.unlink(name);
+                parent.symlink(name, target)?
+            }
+            _ => continue,
+        };
+
+        if entry.uid != 0 || entry.gid != 0 {
+            node.chown(entry.uid, entry.gid)?;
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_entry(out: &mut Vec<u8>, name: &str, ino: u32, mode: u32, nlink: u32, data: &[u8]) {
+        let fields = [
+            ino,
+            mode,
+            0,
+            0,
+            nlink,
+            0,
+            data.len() as u32,
+            0,
+            0,
+            0,
+            0,
+            name.len() as u32 + 1,
+            0,
+        ];
+        out.extend_from_slice(NEWC_MAGIC);
+        for value in fields {
+            out.extend_from_slice(alloc::format!("{:08X}", value).as_bytes());
+        }
+        out.extend_from_slice(name.as_bytes());
+        out.push(0);
+        out.resize(pad4(out.len()), 0);
+        out.extend_from_slice(data);
+        out.resize(pad4(out.len()), 0);
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut archive = Vec::new();
+        push_entry(&mut archive, ".", 1, S_IFDIR | 0o755, 2, &[]);
+        push_entry(&mut archive, "./sbin", 2, S_IFDIR | 0o755, 2, &[]);
+        push_entry(
+            &mut archive,
+            "./sbin/init",
+            3,
+            S_IFREG | 0o755,
+            1,
+            b"\x7fELF",
+        );
+        push_entry(&mut archive, "bin/sh", 4, S_IFLNK | 0o777, 1, b"/sbin/init");
+        push_entry(&mut archive, TRAILER, 0, 0, 1, &[]);
+        archive
+    }
+
+    #[test]
+    fn test_entries() {
+        let archive = sample();
+        let parsed: Vec<CpioEntry> = entries(&archive).map(|e| e.unwrap()).collect();
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed[0].path, "");
+        assert_eq!(parsed[1].path, "sbin");
+        assert_eq!(parsed[2].path, "sbin/init");
+        assert_eq!(parsed[2].mode & S_IFMT, S_IFREG);
+        assert_eq!(parsed[2].data, b"\x7fELF");
+        assert_eq!(parsed[3].path, "bin/sh");
+        assert_eq!(parsed[3].data, b"/sbin/init");
+    }
+
+    #[test]
+    fn test_entries_stop_at_trailer() {
+        let mut archive = sample();
+        archive.extend_from_slice(&[0xFF; 64]);
+        assert_eq!(entries(&archive).count(), 4);
+    }
+
+    #[test]
+    fn test_entries_reject_corruption() {
+        let mut archive = sample();
+        archive[0] = b'1';
+        let mut iter = entries(&archive);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        // Truncated data
+        let archive = sample();
+        let truncated = &archive[..HEADER_SIZE + 8];
+        assert!(entries(truncated).any(|e| e.is_err()));
+    }
+}
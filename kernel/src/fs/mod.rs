@@ -6,10 +6,15 @@
 
 use alloc::{collections::BTreeMap, format, string::String, sync::Arc, vec, vec::Vec};
 
-use spin::RwLock;
+use spin::Mutex;
 
-use crate::error::KernelError;
+use crate::{
+    error::KernelError,
+    sched::workqueue::{self, Work},
+    sync::{lockdep, rcu::Rcu},
+};
 
+pub mod acl;
 #[cfg(target_arch = "aarch64")]
 pub mod bare_lock;
 pub mod blockdev;
@@ -20,7 +25,9 @@ pub mod ext4;
 pub mod fat32;
 pub mod file;
 pub mod flock;
+pub mod initramfs;
 pub mod inotify;
+pub mod p9;
 pub mod pipe;
 pub mod procfs;
 pub mod pty;
@@ -49,6 +56,13 @@ pub const NAME_MAX: usize = 255;
 /// to be generous while still detecting infinite cycles.
 pub const SYMLINK_MAX_DEPTH: usize = 40;
 
+/// `fallocate` flag: do not change the file size.
+pub const FALLOC_FL_KEEP_SIZE: u32 = 0x01;
+
+/// `fallocate` flag: deallocate the range, leaving a hole that reads as
+/// zeros. Must be combined with [`FALLOC_FL_KEEP_SIZE`].
+pub const FALLOC_FL_PUNCH_HOLE: u32 = 0x02;
+
 /// Filesystem node types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NodeType {
@@ -121,6 +135,25 @@ impl Permissions {
         }
     }
 
+    /// Unix mode bits of these permissions, the inverse of
+    /// [`Permissions::from_mode`].
+    pub fn to_mode(&self) -> u32 {
+        [
+            (self.owner_read, 0o400),
+            (self.owner_write, 0o200),
+            (self.owner_exec, 0o100),
+            (self.group_read, 0o040),
+            (self.group_write, 0o020),
+            (self.group_exec, 0o010),
+            (self.other_read, 0o004),
+            (self.other_write, 0o002),
+            (self.other_exec, 0o001),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |mode, (_, bit)| mode | bit)
+    }
+
     /// Check if the given uid/gid has read access.
     pub fn can_read(&self, uid: u32, gid: u32, file_uid: u32, file_gid: u32) -> bool {
         if uid == 0 {
@@ -164,6 +197,40 @@ impl Permissions {
     }
 }
 
+/// The access ACL stored on `node`, if it has a valid one.
+fn node_acl(node: &dyn VfsNode) -> Option<acl::Acl> {
+    let bytes = node.get_xattr(acl::ACL_ACCESS_XATTR).ok()?;
+    acl::Acl::parse(&bytes).ok()
+}
+
+/// Check whether `creds` may access `node` with `mask` (a combination of
+/// `process::creds::ACCESS_*` bits), using the node's access ACL if it has
+/// one and otherwise its owner, group and mode.
+///
+/// Nodes that cannot report metadata (e.g. some synthetic device nodes) are
+/// not restricted.
+pub fn check_access(
+    node: &dyn VfsNode,
+    creds: &crate::process::creds::Credentials,
+    mask: u32,
+) -> Result<(), KernelError> {
+    let Ok(meta) = node.metadata() else {
+        return Ok(());
+    };
+    let is_dir = meta.node_type == NodeType::Directory;
+    let allowed = match node_acl(node) {
+        Some(acl) if !creds.is_privileged() => acl.permits(creds, meta.uid, meta.gid, mask),
+        _ => creds.may_access(meta.uid, meta.gid, &meta.permissions, is_dir, mask),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(KernelError::FsError(
+            crate::error::FsError::PermissionDenied,
+        ))
+    }
+}
+
 /// File metadata
 #[derive(Debug, Clone)]
 pub struct Metadata {
@@ -226,6 +293,34 @@ pub trait VfsNode: Send + Sync {
         })
     }
 
+    /// Number of directory entries referring to this node (`st_nlink`).
+    ///
+    /// Default: 1, for filesystems that do not track hard links.
+    fn link_count(&self) -> u32 {
+        1
+    }
+
+    /// Move the entry `old_name` of this directory to `new_name` in
+    /// `new_dir`, atomically replacing any existing entry there.
+    ///
+    /// Follows `rename(2)`: a directory may only replace an empty directory
+    /// and a non-directory only a non-directory, a directory cannot be moved
+    /// into its own subtree, and renaming onto another link to the same
+    /// inode does nothing. `new_dir` must belong to the same filesystem,
+    /// otherwise `FsError::CrossDevice` is returned.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `NotImplemented`; callers fall back to copy + unlink.
+    fn rename(
+        &self,
+        _old_name: &str,
+        _new_dir: &dyn VfsNode,
+        _new_name: &str,
+    ) -> Result<(), KernelError> {
+        Err(KernelError::NotImplemented { feature: "rename" })
+    }
+
     /// Create a symbolic link in this directory.
     ///
     /// Creates a new symlink entry named `name` in this directory node
@@ -281,6 +376,72 @@ pub trait VfsNode: Send + Sync {
         Err(KernelError::NotImplemented { feature: "chmod" })
     }
 
+    /// Change the owner and group of this node
+    fn chown(&self, _uid: u32, _gid: u32) -> Result<(), KernelError> {
+        Err(KernelError::NotImplemented { feature: "chown" })
+    }
+
+    /// Get the value of the extended attribute `name`.
+    ///
+    /// # Default Implementation
+    ///
+    /// Uses the in-memory [`xattr`] store keyed by this node's inode number.
+    /// Filesystems with on-disk attribute storage (BlockFS) override the four
+    /// xattr methods.
+    fn get_xattr(&self, name: &str) -> Result<Vec<u8>, KernelError> {
+        xattr::getxattr(self.metadata()?.inode, name)
+    }
+
+    /// Create or replace the extended attribute `name`.
+    ///
+    /// `flags` takes `xattr::XATTR_CREATE` / `xattr::XATTR_REPLACE`.
+    fn set_xattr(&self, name: &str, value: &[u8], flags: u32) -> Result<(), KernelError> {
+        xattr::setxattr(self.metadata()?.inode, name, value, flags)
+    }
+
+    /// List the names of all extended attributes on this node.
+    fn list_xattr(&self) -> Result<Vec<String>, KernelError> {
+        xattr::listxattr(self.metadata()?.inode)
+    }
+
+    /// Remove the extended attribute `name`.
+    fn remove_xattr(&self, name: &str) -> Result<(), KernelError> {
+        xattr::removexattr(self.metadata()?.inode, name)
+    }
+
+    /// Find the first offset at or after `offset` that holds data
+    /// (`lseek` `SEEK_DATA`).
+    ///
+    /// Returns `None` when there is no data at or after `offset`.
+    ///
+    /// # Default Implementation
+    ///
+    /// Treats the whole file as data. Filesystems with sparse files
+    /// (BlockFS) override this and [`VfsNode::seek_hole`].
+    fn seek_data(&self, offset: usize) -> Result<Option<usize>, KernelError> {
+        let size = self.metadata()?.size;
+        Ok((offset < size).then_some(offset))
+    }
+
+    /// Find the first hole at or after `offset` (`lseek` `SEEK_HOLE`).
+    ///
+    /// End of file counts as a hole. Returns `None` when `offset` is at or
+    /// beyond end of file.
+    fn seek_hole(&self, offset: usize) -> Result<Option<usize>, KernelError> {
+        let size = self.metadata()?.size;
+        Ok((offset < size).then_some(size))
+    }
+
+    /// Allocate or deallocate the byte range `offset..offset + len`.
+    ///
+    /// `mode` is 0 (allocate, extending the file if needed) or a combination
+    /// of `FALLOC_FL_*` flags.
+    fn fallocate(&self, _mode: u32, _offset: usize, _len: usize) -> Result<(), KernelError> {
+        Err(KernelError::NotImplemented {
+            feature: "fallocate",
+        })
+    }
+
     /// Poll readiness for I/O multiplexing (poll/epoll).
     ///
     /// Returns a bitmask of ready events using POLL* constants:
@@ -333,8 +494,9 @@ pub struct Vfs {
     /// Root filesystem
     root_fs: Option<Arc<dyn Filesystem>>,
 
-    /// Mount points
-    mounts: BTreeMap<String, Arc<dyn Filesystem>>,
+    /// Mount points. RCU-protected so that path resolution never waits for
+    /// a mount or unmount, and mounting needs only the VFS read lock.
+    mounts: Rcu<BTreeMap<String, Arc<dyn Filesystem>>>,
 
     /// Legacy global working directory (fallback only).
     /// Per-process CWD is tracked in `process::cwd::ProcessCwd` and
@@ -348,7 +510,7 @@ impl Vfs {
     pub fn new() -> Self {
         Self {
             root_fs: None,
-            mounts: BTreeMap::new(),
+            mounts: Rcu::new(BTreeMap::new()),
             cwd: String::from("/"),
         }
     }
@@ -371,17 +533,18 @@ impl Vfs {
     }
 
     /// Mount a filesystem at the specified path
-    pub fn mount(&mut self, path: String, fs: Arc<dyn Filesystem>) -> Result<(), KernelError> {
+    pub fn mount(&self, path: String, fs: Arc<dyn Filesystem>) -> Result<(), KernelError> {
         if self.root_fs.is_none() {
             return Err(KernelError::FsError(crate::error::FsError::NoRootFs));
         }
 
-        if self.mounts.contains_key(&path) {
-            return Err(KernelError::FsError(crate::error::FsError::AlreadyMounted));
-        }
-
-        self.mounts.insert(path, fs);
-        Ok(())
+        self.mounts.update(|mounts| {
+            if mounts.contains_key(&path) {
+                return Err(KernelError::FsError(crate::error::FsError::AlreadyMounted));
+            }
+            mounts.insert(path, fs);
+            Ok(())
+        })
     }
 
     /// Mount a filesystem by type at the specified path
@@ -406,6 +569,51 @@ impl Vfs {
         }
     }
 
+    /// Mount the filesystem stored on a registered block device (e.g.
+    /// `/dev/loop0`) at the specified path
+    pub fn mount_block_device(
+        &mut self,
+        path: &str,
+        fs_type: &str,
+        device: &str,
+    ) -> Result<(), KernelError> {
+        let queue = crate::drivers::block::queue(device).ok_or(KernelError::NotFound {
+            resource: "block device",
+            id: 0,
+        })?;
+        let fs: Arc<dyn Filesystem> = match fs_type {
+            "blockfs" => {
+                let backend = blockfs::BlockDeviceBackend::with_queue(queue)?;
+                Arc::new(blockfs::BlockFs::open_existing(Arc::new(Mutex::new(
+                    backend,
+                )))?)
+            }
+            _ => return Err(KernelError::FsError(crate::error::FsError::UnknownFsType)),
+        };
+
+        if path == "/" {
+            self.mount_root(fs)
+        } else {
+            self.mount(path.into(), fs)
+        }
+    }
+
+    /// Mount the directory a virtio-9p device exports under mount tag `tag`
+    /// at the specified path
+    pub fn mount_9p(&mut self, path: &str, tag: &str) -> Result<(), KernelError> {
+        let transport = crate::drivers::virtio::p9::get(tag).ok_or(KernelError::NotFound {
+            resource: "9p mount tag",
+            id: 0,
+        })?;
+        let fs: Arc<dyn Filesystem> = Arc::new(p9::P9Fs::mount(transport)?);
+
+        if path == "/" {
+            self.mount_root(fs)
+        } else {
+            self.mount(path.into(), fs)
+        }
+    }
+
     /// Replace the root filesystem (used for persistent BlockFS mount at boot).
     ///
     /// The previous root filesystem (if any) is dropped. Mount points under
@@ -415,9 +623,9 @@ impl Vfs {
     }
 
     /// Unmount a filesystem at the specified path
-    pub fn unmount(&mut self, path: &str) -> Result<(), KernelError> {
+    pub fn unmount(&self, path: &str) -> Result<(), KernelError> {
         self.mounts
-            .remove(path)
+            .update(|mounts| mounts.remove(path))
             .ok_or(KernelError::FsError(crate::error::FsError::NotMounted))
             .map(|_| ())
     }
@@ -482,12 +690,18 @@ impl Vfs {
             format!("{}/{}", cwd, path)
         };
 
-        // Check if path is under a mount point
-        for (mount_path, fs) in self.mounts.iter().rev() {
-            if path.starts_with(mount_path) {
-                let relative_path = &path[mount_path.len()..];
-                return self.traverse_path(fs.root(), relative_path, follow_last, symlink_depth);
-            }
+        // Check if path is under a mount point. Traversal may sleep, so it
+        // happens after the read-side critical section.
+        let mounted = self
+            .mounts
+            .read()
+            .iter()
+            .rev()
+            .find(|(mount_path, _)| path.starts_with(mount_path.as_str()))
+            .map(|(mount_path, fs)| (mount_path.len(), fs.clone()));
+        if let Some((prefix_len, fs)) = mounted {
+            let relative_path = &path[prefix_len..];
+            return self.traverse_path(fs.root(), relative_path, follow_last, symlink_depth);
         }
 
         // Use root filesystem
@@ -527,8 +741,9 @@ impl Vfs {
                     path_stack.pop();
                     node = path_stack
                         .last()
-                        .ok_or(KernelError::LegacyError {
-                            message: "internal error: path stack unexpectedly empty",
+                        .ok_or(KernelError::InvalidState {
+                            expected: "non-empty path stack",
+                            actual: "empty path stack",
                         })?
                         .clone();
                 }
@@ -699,7 +914,7 @@ impl Vfs {
         }
 
         // Mounted filesystems
-        for (path, fs) in &self.mounts {
+        for (path, fs) in self.mounts.read().iter() {
             result.push((path.clone(), String::from(fs.name()), fs.is_readonly()));
         }
 
@@ -713,8 +928,10 @@ impl Vfs {
             root.sync()?;
         }
 
-        // Sync all mounted filesystems
-        for fs in self.mounts.values() {
+        // Sync all mounted filesystems (outside the read-side critical
+        // section, since syncing does I/O)
+        let mounted: Vec<Arc<dyn Filesystem>> = self.mounts.read().values().cloned().collect();
+        for fs in mounted {
             fs.sync()?;
         }
 
@@ -723,24 +940,40 @@ impl Vfs {
 }
 
 /// Global VFS instance using OnceLock for safe initialization.
-static VFS_LOCK: crate::sync::once_lock::OnceLock<RwLock<Vfs>> =
+static VFS_LOCK: crate::sync::once_lock::OnceLock<lockdep::RwLock<Vfs>> =
     crate::sync::once_lock::OnceLock::new();
 
 /// Get the VFS instance (unified for all architectures).
 ///
 /// Panics if the VFS has not been initialized via [`init`].
 /// Prefer [`try_get_vfs`] in contexts where a panic is unacceptable.
-pub fn get_vfs() -> &'static RwLock<Vfs> {
+pub fn get_vfs() -> &'static lockdep::RwLock<Vfs> {
     VFS_LOCK
         .get()
         .expect("VFS not initialized: init() was not called")
 }
 
 /// Try to get the VFS instance without panicking
-pub fn try_get_vfs() -> Option<&'static RwLock<Vfs>> {
+pub fn try_get_vfs() -> Option<&'static lockdep::RwLock<Vfs>> {
     VFS_LOCK.get()
 }
 
+/// Interval between periodic write-backs of every mounted filesystem.
+const WRITEBACK_INTERVAL_MS: u64 = 5000;
+
+static WRITEBACK_WORK: Work = Work::new(writeback, 0);
+
+/// Periodic write-back (the block cache flusher). Skips a round rather than
+/// waiting if a mount or unmount holds the VFS, then re-arms itself.
+fn writeback(_data: usize) {
+    if let Some(vfs) = try_get_vfs().and_then(|vfs| vfs.try_read()) {
+        if let Err(_e) = vfs.sync() {
+            println!("[VFS] Write-back failed: {:?}", _e);
+        }
+    }
+    workqueue::queue_delayed_work(&WRITEBACK_WORK, WRITEBACK_INTERVAL_MS);
+}
+
 /// Initialize the VFS with a RAM filesystem as root
 pub fn init() {
     #[allow(unused_imports)]
@@ -750,7 +983,7 @@ pub fn init() {
 
     println!("[VFS] Creating VFS structure...");
     let vfs = Vfs::new();
-    let vfs_lock = RwLock::new(vfs);
+    let vfs_lock = lockdep::RwLock::new(vfs);
 
     match VFS_LOCK.set(vfs_lock) {
         Ok(()) => println!("[VFS] VFS initialized successfully"),
@@ -763,19 +996,25 @@ pub fn init() {
     // Create and mount filesystems
     #[cfg(feature = "alloc")]
     {
-        println!("[VFS] Creating RAM filesystem...");
-
-        // Create a RAM filesystem as the root
-        let ramfs = ramfs::RamFs::new();
+        // Boot with a tmpfs root when the bootloader supplied an initramfs,
+        // otherwise with a RAM filesystem.
+        let initramfs_image = initramfs::boot_image();
+        let root_fs: Arc<dyn Filesystem> = if initramfs_image.is_some() {
+            println!("[VFS] Creating tmpfs root for initramfs...");
+            Arc::new(tmpfs::TmpFs::new(0))
+        } else {
+            println!("[VFS] Creating RAM filesystem...");
+            Arc::new(ramfs::RamFs::new())
+        };
 
         // Mount as root
         {
             let vfs = get_vfs();
             let mut vfs_guard = vfs.write();
-            vfs_guard.mount_root(Arc::new(ramfs)).ok();
+            vfs_guard.mount_root(root_fs).ok();
         }
 
-        println!("[VFS] RAM filesystem mounted as root");
+        println!("[VFS] Root filesystem mounted");
 
         // Create standard directories in root
         {
@@ -895,13 +1134,21 @@ pub fn init() {
 
         println!("[VFS] Populated /etc and subdirectories");
 
+        // Unpack the initramfs over the defaults so its files take precedence.
+        if let Some(image) = initramfs_image {
+            match initramfs::unpack(image) {
+                Ok(count) => println!("[VFS] Unpacked {} initramfs entries", count),
+                Err(_e) => println!("[VFS] WARNING: failed to unpack initramfs: {:?}", _e),
+            }
+        }
+
         // Create DevFS and mount at /dev
         println!("[VFS] Creating device filesystem...");
         let devfs = devfs::DevFs::new();
 
         {
             let vfs = get_vfs();
-            let mut vfs_guard = vfs.write();
+            let vfs_guard = vfs.write();
             vfs_guard.mount("/dev".into(), Arc::new(devfs)).ok();
         }
 
@@ -913,12 +1160,15 @@ pub fn init() {
 
         {
             let vfs = get_vfs();
-            let mut vfs_guard = vfs.write();
+            let vfs_guard = vfs.write();
             vfs_guard.mount("/proc".into(), Arc::new(procfs)).ok();
         }
 
         println!("[VFS] Process filesystem mounted at /proc");
 
+        // Write dirty data back periodically rather than only on sync()
+        workqueue::queue_delayed_work(&WRITEBACK_WORK, WRITEBACK_INTERVAL_MS);
+
         println!("[VFS] Virtual Filesystem initialization complete");
     }
 
@@ -1116,6 +1366,14 @@ mod tests {
         assert!(!perm.other_read);
     }
 
+    #[test]
+    fn test_permissions_to_mode() {
+        assert_eq!(Permissions::from_mode(0o644).to_mode(), 0o644);
+        assert_eq!(Permissions::from_mode(0o751).to_mode(), 0o751);
+        assert_eq!(Permissions::default().to_mode(), 0o755);
+        assert_eq!(Permissions::read_only().to_mode(), 0o444);
+    }
+
     // --- Vfs construction tests ---
 
     #[test]
@@ -0,0 +1,636 @@
+//! 9P2000.L client filesystem
+//!
+//! Mounts a directory exported by a 9P server -- in practice the host,
+//! through a virtio-9p device -- so files edited on the development machine
+//! are visible in the guest at once, without rebuilding a BlockFS image:
+//!
+//! ```text
+//! qemu ... -virtfs local,path=./share,mount_tag=host,security_model=none
+//! mount host /mnt/host 9p
+//! ```
+//!
+//! Every node holds a fid for its file, walked from its parent's, and
+//! clunks it when dropped. Reads and writes go through a second fid opened
+//! on first use. Nothing is cached: each operation is a round trip, so the
+//! guest always sees the host's current files.
+
+pub mod proto;
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use spin::Mutex;
+
+use self::proto::{Attr, Decoder, Encoder, Qid};
+use super::{DirEntry, Filesystem, Metadata, NodeType, Permissions, VfsNode};
+use crate::error::{FsError, KernelError};
+
+/// A channel to a 9P server.
+pub trait Transport: Send + Sync {
+    /// Largest message the transport carries.
+    fn msize(&self) -> u32;
+
+    /// Send the request message `request` and return the reply message.
+    fn rpc(&self, request: &[u8]) -> Result<Vec<u8>, KernelError>;
+}
+
+/// Tag of every request but `Tversion`. Requests are sent one at a time,
+/// so none is ever outstanding alongside another.
+const TAG: u16 = 1;
+
+/// Fid of the root of the export.
+const ROOT_FID: u32 = 0;
+
+/// The 9P session of one mount.
+struct Client {
+    transport: Arc<dyn Transport>,
+    /// Message size agreed in `Tversion`
+    msize: u32,
+    next_fid: AtomicU32,
+}
+
+impl Client {
+    /// Agree on the protocol version and message size.
+    fn connect(transport: Arc<dyn Transport>) -> Result<Self, KernelError> {
+        let request = Encoder::new(proto::TVERSION, proto::NOTAG)
+            .u32(transport.msize())
+            .str(proto::VERSION)
+            .finish();
+        let reply = transport.rpc(&request)?;
+        let mut body = proto::parse_reply(&reply, proto::TVERSION + 1, proto::NOTAG)?;
+        let msize = body.u32()?.min(transport.msize());
+        if body.str()? != proto::VERSION || msize <= proto::IO_HEADER_LEN {
+            return Err(KernelError::FsError(FsError::NotSupported));
+        }
+        Ok(Self {
+            transport,
+            msize,
+            next_fid: AtomicU32::new(ROOT_FID + 1),
+        })
+    }
+
+    fn new_fid(&self) -> u32 {
+        self.next_fid.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Largest payload of a read or write.
+    fn io_size(&self) -> u32 {
+        self.msize - proto::IO_HEADER_LEN
+    }
+
+    /// Send `request` and pass the body of its reply to `f`.
+    fn call<T>(
+        &self,
+        request: &mut Encoder,
+        f: impl FnOnce(&mut Decoder<'_>) -> Result<T, KernelError>,
+    ) -> Result<T, KernelError> {
+        let request = request.finish();
+        let reply = self.transport.rpc(&request)?;
+        let mut body = proto::parse_reply(&reply, request[4] + 1, TAG)?;
+        f(&mut body)
+    }
+
+    fn attach(&self, fid: u32, uname: &str, aname: &str) -> Result<Qid, KernelError> {
+        self.call(
+            Encoder::new(proto::TATTACH, TAG)
+                .u32(fid)
+                .u32(proto::NOFID)
+                .str(uname)
+                .str(aname)
+                .u32(0),
+            |body| body.qid(),
+        )
+    }
+
+    /// Walk `fid` through `names` to `newfid`; no names clones `fid`.
+    fn walk(&self, fid: u32, newfid: u32, names: &[&str]) -> Result<Option<Qid>, KernelError> {
+        let mut request = Encoder::new(proto::TWALK, TAG);
+        request.u32(fid).u32(newfid).u16(names.len() as u16);
+        for name in names {
+            request.str(name);
+        }
+        let qids = self.call(&mut request, |body| {
+            let count = body.u16()?;
+            (0..count)
+                .map(|_| body.qid())
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        if qids.len() < names.len() {
+            // A partial walk does not set up newfid.
+            return Err(KernelError::FsError(FsError::NotFound));
+        }
+        Ok(qids.last().copied())
+    }
+
+    fn clunk(&self, fid: u32) -> Result<(), KernelError> {
+        self.call(Encoder::new(proto::TCLUNK, TAG).u32(fid), |_| Ok(()))
+    }
+
+    fn getattr(&self, fid: u32) -> Result<Attr, KernelError> {
+        self.call(
+            Encoder::new(proto::TGETATTR, TAG)
+                .u32(fid)
+                .u64(proto::GETATTR_BASIC),
+            |body| body.attr(),
+        )
+    }
+
+    fn setattr(
+        &self,
+        fid: u32,
+        valid: u32,
+        mode: u32,
+        uid: u32,
+        gid: u32,
+        size: u64,
+    ) -> Result<(), KernelError> {
+        self.call(
+            Encoder::new(proto::TSETATTR, TAG)
+                .u32(fid)
+                .u32(valid)
+                .u32(mode)
+                .u32(uid)
+                .u32(gid)
+                .u64(size)
+                .u64(0)
+                .u64(0)
+                .u64(0)
+                .u64(0),
+            |_| Ok(()),
+        )
+    }
+
+    fn lopen(&self, fid: u32, flags: u32) -> Result<(), KernelError> {
+        self.call(Encoder::new(proto::TLOPEN, TAG).u32(fid).u32(flags), |_| {
+            Ok(())
+        })
+    }
+
+    /// Create `name` in the directory of `fid`, which becomes the new file
+    /// opened with `flags`.
+    fn lcreate(&self, fid: u32, name: &str, flags: u32, mode: u32) -> Result<Qid, KernelError> {
+        self.call(
+            Encoder::new(proto::TLCREATE, TAG)
+                .u32(fid)
+                .str(name)
+                .u32(flags)
+                .u32(mode)
+                .u32(0),
+            |body| body.qid(),
+        )
+    }
+
+    fn read(&self, fid: u32, offset: u64, buf: &mut [u8]) -> Result<usize, KernelError> {
+        let count = (buf.len() as u32).min(self.io_size());
+        self.call(
+            Encoder::new(proto::TREAD, TAG)
+                .u32(fid)
+                .u64(offset)
+                .u32(count),
+            |body| {
+                let data = body.data()?;
+                let len = data.len().min(buf.len());
+                buf[..len].copy_from_slice(&data[..len]);
+                Ok(len)
+            },
+        )
+    }
+
+    fn write(&self, fid: u32, offset: u64, data: &[u8]) -> Result<usize, KernelError> {
+        let len = data.len().min(self.io_size() as usize);
+        self.call(
+            Encoder::new(proto::TWRITE, TAG)
+                .u32(fid)
+                .u64(offset)
+                .data(&data[..len]),
+            |body| Ok(body.u32()? as usize),
+        )
+    }
+
+    fn readdir(&self, fid: u32, offset: u64) -> Result<Vec<proto::Dirent>, KernelError> {
+        self.call(
+            Encoder::new(proto::TREADDIR, TAG)
+                .u32(fid)
+                .u64(offset)
+                .u32(self.io_size()),
+            |body| proto::parse_dirents(body.data()?),
+        )
+    }
+
+    fn mkdir(&self, fid: u32, name: &str, mode: u32) -> Result<Qid, KernelError> {
+        self.call(
+            Encoder::new(proto::TMKDIR, TAG)
+                .u32(fid)
+                .str(name)
+                .u32(mode)
+                .u32(0),
+            |body| body.qid(),
+        )
+    }
+
+    fn symlink(&self, fid: u32, name: &str, target: &str) -> Result<Qid, KernelError> {
+        self.call(
+            Encoder::new(proto::TSYMLINK, TAG)
+                .u32(fid)
+                .str(name)
+                .str(target)
+                .u32(0),
+            |body| body.qid(),
+        )
+    }
+
+    fn readlink(&self, fid: u32) -> Result<String, KernelError> {
+        self.call(Encoder::new(proto::TREADLINK, TAG).u32(fid), |body| {
+            body.str()
+        })
+    }
+
+    fn link(&self, dir_fid: u32, fid: u32, name: &str) -> Result<(), KernelError> {
+        self.call(
+            Encoder::new(proto::TLINK, TAG)
+                .u32(dir_fid)
+                .u32(fid)
+                .str(name),
+            |_| Ok(()),
+        )
+    }
+
+    fn unlinkat(&self, dir_fid: u32, name: &str, flags: u32) -> Result<(), KernelError> {
+        self.call(
+            Encoder::new(proto::TUNLINKAT, TAG)
+                .u32(dir_fid)
+                .str(name)
+                .u32(flags),
+            |_| Ok(()),
+        )
+    }
+
+    fn renameat(
+        &self,
+        old_dir: u32,
+        old_name: &str,
+        new_dir: u32,
+        new_name: &str,
+    ) -> Result<(), KernelError> {
+        self.call(
+            Encoder::new(proto::TRENAMEAT, TAG)
+                .u32(old_dir)
+                .str(old_name)
+                .u32(new_dir)
+                .str(new_name),
+            |_| Ok(()),
+        )
+    }
+}
+
+/// A fid opened for I/O.
+#[derive(Debug, Clone, Copy)]
+struct OpenFid {
+    fid: u32,
+    writable: bool,
+}
+
+/// A file, directory or symlink on the server.
+struct P9Node {
+    client: Arc<Client>,
+    /// Fid of the file, used for everything but I/O
+    fid: u32,
+    qid: Qid,
+    /// Fid opened for reads and writes, on first use
+    open: Mutex<Option<OpenFid>>,
+}
+
+impl P9Node {
+    fn new(client: Arc<Client>, fid: u32, qid: Qid) -> Arc<Self> {
+        Arc::new(Self {
+            client,
+            fid,
+            qid,
+            open: Mutex::new(None),
+        })
+    }
+
+    /// Node for the entry `name` of this directory.
+    fn walk_to(&self, name: &str) -> Result<Arc<Self>, KernelError> {
+        let fid = self.client.new_fid();
+        let names: &[&str] = if name == "." { &[] } else { &[name] };
+        let qid = self.client.walk(self.fid, fid, names)?.unwrap_or(self.qid);
+        Ok(Self::new(self.client.clone(), fid, qid))
+    }
+
+    /// Fid opened for I/O, read-write if the server allows it.
+    fn open_fid(&self, write: bool) -> Result<u32, KernelError> {
+        let mut open = self.open.lock();
+        if let Some(open) = *open {
+            if write && !open.writable {
+                return Err(KernelError::FsError(FsError::PermissionDenied));
+            }
+            return Ok(open.fid);
+        }
+        let fid = self.client.new_fid();
+        self.client.walk(self.fid, fid, &[])?;
+        let writable = match self.client.lopen(fid, proto::O_RDWR) {
+            Ok(()) => true,
+            Err(_) if !write => {
+                if let Err(e) = self.client.lopen(fid, proto::O_RDONLY) {
+                    let _ = self.client.clunk(fid);
+                    return Err(e);
+                }
+                false
+            }
+            Err(e) => {
+                let _ = self.client.clunk(fid);
+                return Err(e);
+            }
+        };
+        *open = Some(OpenFid { fid, writable });
+        Ok(fid)
+    }
+
+    fn attr(&self) -> Result<Attr, KernelError> {
+        self.client.getattr(self.fid)
+    }
+}
+
+impl Drop for P9Node {
+    fn drop(&mut self) {
+        if let Some(open) = self.open.get_mut().take() {
+            let _ = self.client.clunk(open.fid);
+        }
+        let _ = self.client.clunk(self.fid);
+    }
+}
+
+/// Node type of a file with `st_mode` `mode`.
+fn mode_node_type(mode: u32) -> NodeType {
+    match mode & 0o170000 {
+        0o040000 => NodeType::Directory,
+        0o120000 => NodeType::Symlink,
+        0o020000 => NodeType::CharDevice,
+        0o060000 => NodeType::BlockDevice,
+        0o010000 => NodeType::Pipe,
+        0o140000 => NodeType::Socket,
+        _ => NodeType::File,
+    }
+}
+
+impl VfsNode for P9Node {
+    fn node_type(&self) -> NodeType {
+        if self.qid.is_dir() {
+            NodeType::Directory
+        } else if self.qid.is_symlink() {
+            NodeType::Symlink
+        } else {
+            NodeType::File
+        }
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<usize, KernelError> {
+        if self.qid.is_dir() {
+            return Err(KernelError::FsError(FsError::IsADirectory));
+        }
+        let fid = self.open_fid(false)?;
+        let mut total = 0;
+        while total < buffer.len() {
+            let read = self
+                .client
+                .read(fid, (offset + total) as u64, &mut buffer[total..])?;
+            if read == 0 {
+                break;
+            }
+            total += read;
+        }
+        Ok(total)
+    }
+
+    fn write(&self, offset: usize, data: &[u8]) -> Result<usize, KernelError> {
+        if self.qid.is_dir() {
+            return Err(KernelError::FsError(FsError::IsADirectory));
+        }
+        let fid = self.open_fid(true)?;
+        let mut total = 0;
+        while total < data.len() {
+            let written = self
+                .client
+                .write(fid, (offset + total) as u64, &data[total..])?;
+            if written == 0 {
+                break;
+            }
+            total += written;
+        }
+        Ok(total)
+    }
+
+    fn metadata(&self) -> Result<Metadata, KernelError> {
+        let attr = self.attr()?;
+        Ok(Metadata {
+            node_type: mode_node_type(attr.mode),
+            size: attr.size as usize,
+            permissions: Permissions::from_mode(attr.mode),
+            uid: attr.uid,
+            gid: attr.gid,
+            created: attr.ctime,
+            modified: attr.mtime,
+            accessed: attr.atime,
+            inode: attr.qid.path,
+        })
+    }
+
+    fn link_count(&self) -> u32 {
+        self.attr().map_or(1, |attr| attr.nlink as u32)
+    }
+
+    fn readdir(&self) -> Result<Vec<DirEntry>, KernelError> {
+        if !self.qid.is_dir() {
+            return Err(KernelError::FsError(FsError::NotADirectory));
+        }
+        // A directory is read through its own fid, opened for the listing.
+        let fid = self.client.new_fid();
+        self.client.walk(self.fid, fid, &[])?;
+        let listing = self.client.lopen(fid, proto::O_RDONLY).and_then(|()| {
+            let mut entries = Vec::new();
+            let mut offset = 0;
+            loop {
+                let batch = self.client.readdir(fid, offset)?;
+                let Some(last) = batch.last() else {
+                    return Ok(entries);
+                };
+                offset = last.offset;
+                entries.extend(batch.into_iter().map(|entry| DirEntry {
+                    node_type: if entry.qid.is_dir() {
+                        NodeType::Directory
+                    } else if entry.qid.is_symlink() {
+                        NodeType::Symlink
+                    } else {
+                        NodeType::File
+                    },
+                    inode: entry.qid.path,
+                    name: entry.name,
+                }));
+            }
+        });
+        let _ = self.client.clunk(fid);
+        listing
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn VfsNode>, KernelError> {
+        if !self.qid.is_dir() {
+            return Err(KernelError::FsError(FsError::NotADirectory));
+        }
+        Ok(self.walk_to(name)? as Arc<dyn VfsNode>)
+    }
+
+    fn create(
+        &self,
+        name: &str,
+        permissions: Permissions,
+    ) -> Result<Arc<dyn VfsNode>, KernelError> {
+        // Tlcreate turns a clone of the directory fid into the new file,
+        // opened; it serves as the node's I/O fid.
+        let open_fid = self.client.new_fid();
+        self.client.walk(self.fid, open_fid, &[])?;
+        if let Err(e) = self
+            .client
+            .lcreate(open_fid, name, proto::O_RDWR, permissions.to_mode())
+        {
+            let _ = self.client.clunk(open_fid);
+            return Err(e);
+        }
+        let node = self.walk_to(name)?;
+        *node.open.lock() = Some(OpenFid {
+            fid: open_fid,
+            writable: true,
+        });
+        Ok(node as Arc<dyn VfsNode>)
+    }
+
+    fn mkdir(&self, name: &str, permissions: Permissions) -> Result<Arc<dyn VfsNode>, KernelError> {
+        self.client.mkdir(self.fid, name, permissions.to_mode())?;
+        Ok(self.walk_to(name)? as Arc<dyn VfsNode>)
+    }
+
+    fn unlink(&self, name: &str) -> Result<(), KernelError> {
+        match self.client.unlinkat(self.fid, name, 0) {
+            Err(KernelError::FsError(FsError::IsADirectory)) => {
+                self.client.unlinkat(self.fid, name, proto::AT_REMOVEDIR)
+            }
+            result => result,
+        }
+    }
+
+    fn truncate(&self, size: usize) -> Result<(), KernelError> {
+        self.client
+            .setattr(self.fid, proto::SETATTR_SIZE, 0, 0, 0, size as u64)
+    }
+
+    fn link(&self, name: &str, target: Arc<dyn VfsNode>) -> Result<(), KernelError> {
+        let target = same_mount(self, target.as_ref())?;
+        self.client.link(self.fid, target.fid, name)
+    }
+
+    fn rename(
+        &self,
+        old_name: &str,
+        new_dir: &dyn VfsNode,
+        new_name: &str,
+    ) -> Result<(), KernelError> {
+        let new_dir = same_mount(self, new_dir)?;
+        self.client
+            .renameat(self.fid, old_name, new_dir.fid, new_name)
+    }
+
+    fn symlink(&self, name: &str, target: &str) -> Result<Arc<dyn VfsNode>, KernelError> {
+        self.client.symlink(self.fid, name, target)?;
+        Ok(self.walk_to(name)? as Arc<dyn VfsNode>)
+    }
+
+    fn readlink(&self) -> Result<String, KernelError> {
+        if !self.qid.is_symlink() {
+            return Err(KernelError::FsError(FsError::NotASymlink));
+        }
+        self.client.readlink(self.fid)
+    }
+
+    fn chmod(&self, permissions: Permissions) -> Result<(), KernelError> {
+        self.client.setattr(
+            self.fid,
+            proto::SETATTR_MODE,
+            permissions.to_mode(),
+            0,
+            0,
+            0,
+        )
+    }
+
+    fn chown(&self, uid: u32, gid: u32) -> Result<(), KernelError> {
+        self.client.setattr(
+            self.fid,
+            proto::SETATTR_UID | proto::SETATTR_GID,
+            0,
+            uid,
+            gid,
+            0,
+        )
+    }
+
+    fn as_any(&self) -> Option<&dyn core::any::Any> {
+        Some(self)
+    }
+}
+
+/// `other` as a node of the same mount as `node`, or `CrossDevice`.
+fn same_mount<'a>(node: &P9Node, other: &'a dyn VfsNode) -> Result<&'a P9Node, KernelError> {
+    other
+        .as_any()
+        .and_then(|any| any.downcast_ref::<P9Node>())
+        .filter(|other| Arc::ptr_eq(&other.client, &node.client))
+        .ok_or(KernelError::FsError(FsError::CrossDevice))
+}
+
+/// A directory tree exported by a 9P server.
+pub struct P9Fs {
+    root: Arc<P9Node>,
+}
+
+impl P9Fs {
+    /// Attach to the export of the server behind `transport` as root.
+    pub fn mount(transport: Arc<dyn Transport>) -> Result<Self, KernelError> {
+        let client = Arc::new(Client::connect(transport)?);
+        let qid = client.attach(ROOT_FID, "root", "")?;
+        Ok(Self {
+            root: P9Node::new(client, ROOT_FID, qid),
+        })
+    }
+}
+
+impl Filesystem for P9Fs {
+    fn root(&self) -> Arc<dyn VfsNode> {
+        self.root.clone() as Arc<dyn VfsNode>
+    }
+
+    fn name(&self) -> &str {
+        "9p"
+    }
+
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    fn sync(&self) -> Result<(), KernelError> {
+        // Writes go straight to the server; there is nothing to flush.
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_node_type() {
+        assert_eq!(mode_node_type(0o040755), NodeType::Directory);
+        assert_eq!(mode_node_type(0o100644), NodeType::File);
+        assert_eq!(mode_node_type(0o120777), NodeType::Symlink);
+        assert_eq!(mode_node_type(0o020666), NodeType::CharDevice);
+    }
+}
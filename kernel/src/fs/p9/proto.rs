@@ -0,0 +1,389 @@
+//! 9P2000.L message encoding and decoding
+//!
+//! Every message starts with a 7-byte header: `size[4] type[1] tag[2]`,
+//! where `size` counts the whole message. Integers are little-endian and
+//! strings are a 2-byte length followed by UTF-8 bytes without a
+//! terminator.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::error::{FsError, KernelError};
+
+/// Protocol version requested in `Tversion`
+pub const VERSION: &str = "9P2000.L";
+
+/// Size of the message header
+pub const HEADER_LEN: usize = 7;
+
+/// Header bytes of `Tread`/`Rread` and `Twrite` beyond the payload
+/// (`P9_IOHDRSZ`)
+pub const IO_HEADER_LEN: u32 = 24;
+
+/// The tag of `Tversion`, which no other request may use
+pub const NOTAG: u16 = !0;
+
+/// "No fid", the `afid` of an unauthenticated `Tattach`
+pub const NOFID: u32 = !0;
+
+// Message types. Each reply is its request's type plus one.
+pub const RLERROR: u8 = 7;
+pub const TLOPEN: u8 = 12;
+pub const TLCREATE: u8 = 14;
+pub const TSYMLINK: u8 = 16;
+pub const TREADLINK: u8 = 22;
+pub const TGETATTR: u8 = 24;
+pub const TSETATTR: u8 = 26;
+pub const TREADDIR: u8 = 40;
+pub const TLINK: u8 = 70;
+pub const TMKDIR: u8 = 72;
+pub const TRENAMEAT: u8 = 74;
+pub const TUNLINKAT: u8 = 76;
+pub const TVERSION: u8 = 100;
+pub const TATTACH: u8 = 104;
+pub const TWALK: u8 = 110;
+pub const TREAD: u8 = 116;
+pub const TWRITE: u8 = 118;
+pub const TCLUNK: u8 = 120;
+
+/// Qid type bit: directory
+pub const QTDIR: u8 = 0x80;
+/// Qid type bit: symbolic link
+pub const QTSYMLINK: u8 = 0x02;
+
+/// `Tgetattr` mask for the fields of `stat(2)`
+pub const GETATTR_BASIC: u64 = 0x7ff;
+
+/// `Tsetattr` valid bits
+pub const SETATTR_MODE: u32 = 0x1;
+pub const SETATTR_UID: u32 = 0x2;
+pub const SETATTR_GID: u32 = 0x4;
+pub const SETATTR_SIZE: u32 = 0x8;
+
+/// Linux open flags used with `Tlopen`/`Tlcreate`
+pub const O_RDONLY: u32 = 0;
+pub const O_RDWR: u32 = 2;
+
+/// `Tunlinkat` flag: remove a directory
+pub const AT_REMOVEDIR: u32 = 0x200;
+
+/// Unique identity of a file on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Qid {
+    /// `QT*` type bits
+    pub kind: u8,
+    pub version: u32,
+    /// Unique per file, like an inode number
+    pub path: u64,
+}
+
+impl Qid {
+    pub fn is_dir(&self) -> bool {
+        self.kind & QTDIR != 0
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.kind & QTSYMLINK != 0
+    }
+}
+
+/// File attributes from `Rgetattr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attr {
+    pub qid: Qid,
+    /// `st_mode`, file type bits included
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub nlink: u64,
+    pub size: u64,
+    /// Access, modification and change times, in seconds
+    pub atime: u64,
+    pub mtime: u64,
+    pub ctime: u64,
+}
+
+/// A directory entry from `Rreaddir`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirent {
+    pub qid: Qid,
+    /// Offset to pass to `Treaddir` to continue after this entry
+    pub offset: u64,
+    pub name: String,
+}
+
+/// Builds one request message.
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new(kind: u8, tag: u16) -> Self {
+        let mut encoder = Self { buf: Vec::new() };
+        encoder.u32(0).u8(kind).u16(tag);
+        encoder
+    }
+
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.buf.push(value);
+        self
+    }
+
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn str(&mut self, value: &str) -> &mut Self {
+        self.u16(value.len() as u16);
+        self.buf.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    /// A `count[4]` followed by `data`.
+    pub fn data(&mut self, data: &[u8]) -> &mut Self {
+        self.u32(data.len() as u32);
+        self.buf.extend_from_slice(data);
+        self
+    }
+
+    /// The finished message, with its size filled in.
+    pub fn finish(&mut self) -> Vec<u8> {
+        let size = self.buf.len() as u32;
+        self.buf[0..4].copy_from_slice(&size.to_le_bytes());
+        core::mem::take(&mut self.buf)
+    }
+}
+
+/// A reply that ended early or holds invalid data.
+fn malformed() -> KernelError {
+    KernelError::FsError(FsError::CorruptedData)
+}
+
+/// Reads the fields of a reply body.
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], KernelError> {
+        if self.buf.len() < len {
+            return Err(malformed());
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, KernelError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, KernelError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, KernelError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, KernelError> {
+        let b = self.bytes(8)?;
+        Ok(u64::from_le_bytes([
+            b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+        ]))
+    }
+
+    pub fn str(&mut self) -> Result<String, KernelError> {
+        let len = self.u16()? as usize;
+        let bytes = self.bytes(len)?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| malformed())
+    }
+
+    /// A `count[4]` followed by that many bytes.
+    pub fn data(&mut self) -> Result<&'a [u8], KernelError> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    pub fn qid(&mut self) -> Result<Qid, KernelError> {
+        Ok(Qid {
+            kind: self.u8()?,
+            version: self.u32()?,
+            path: self.u64()?,
+        })
+    }
+
+    /// The body of `Rgetattr`.
+    pub fn attr(&mut self) -> Result<Attr, KernelError> {
+        let _valid = self.u64()?;
+        let qid = self.qid()?;
+        let mode = self.u32()?;
+        let uid = self.u32()?;
+        let gid = self.u32()?;
+        let nlink = self.u64()?;
+        let _rdev = self.u64()?;
+        let size = self.u64()?;
+        let _blksize = self.u64()?;
+        let _blocks = self.u64()?;
+        let atime = self.u64()?;
+        let _atime_nsec = self.u64()?;
+        let mtime = self.u64()?;
+        let _mtime_nsec = self.u64()?;
+        let ctime = self.u64()?;
+        Ok(Attr {
+            qid,
+            mode,
+            uid,
+            gid,
+            nlink,
+            size,
+            atime,
+            mtime,
+            ctime,
+        })
+    }
+}
+
+/// The entries packed in the data of `Rreaddir`.
+pub fn parse_dirents(data: &[u8]) -> Result<Vec<Dirent>, KernelError> {
+    let mut decoder = Decoder::new(data);
+    let mut entries = Vec::new();
+    while !decoder.buf.is_empty() {
+        let qid = decoder.qid()?;
+        let offset = decoder.u64()?;
+        let _kind = decoder.u8()?;
+        let name = decoder.str()?;
+        entries.push(Dirent { qid, offset, name });
+    }
+    Ok(entries)
+}
+
+/// The error for Linux errno `errno` from `Rlerror`.
+pub fn errno_error(errno: u32) -> KernelError {
+    let error = match errno {
+        1 | 13 => FsError::PermissionDenied, // EPERM, EACCES
+        2 => FsError::NotFound,              // ENOENT
+        17 => FsError::AlreadyExists,        // EEXIST
+        18 => FsError::CrossDevice,          // EXDEV
+        20 => FsError::NotADirectory,        // ENOTDIR
+        21 => FsError::IsADirectory,         // EISDIR
+        27 => FsError::FileTooLarge,         // EFBIG
+        28 | 122 => FsError::NoSpace,        // ENOSPC, EDQUOT
+        30 => FsError::ReadOnly,             // EROFS
+        36 => FsError::InvalidPath,          // ENAMETOOLONG
+        38 | 95 => FsError::NotSupported,    // ENOSYS, EOPNOTSUPP
+        39 => FsError::DirectoryNotEmpty,    // ENOTEMPTY
+        40 => FsError::SymlinkLoop,          // ELOOP
+        _ => FsError::IoError,
+    };
+    KernelError::FsError(error)
+}
+
+/// Check that `reply` is a well-formed `kind` reply to the request with
+/// `tag`, and return a decoder for its body. An `Rlerror` becomes the
+/// matching error.
+pub fn parse_reply(reply: &[u8], kind: u8, tag: u16) -> Result<Decoder<'_>, KernelError> {
+    let mut header = Decoder::new(reply);
+    let size = header.u32()? as usize;
+    let reply_kind = header.u8()?;
+    let reply_tag = header.u16()?;
+    if size < HEADER_LEN || size > reply.len() || reply_tag != tag {
+        return Err(malformed());
+    }
+    let mut body = Decoder::new(&reply[HEADER_LEN..size]);
+    match reply_kind {
+        RLERROR => Err(errno_error(body.u32()?)),
+        k if k == kind => Ok(body),
+        _ => Err(malformed()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_version() {
+        let msg = Encoder::new(TVERSION, NOTAG)
+            .u32(8192)
+            .str(VERSION)
+            .finish();
+        assert_eq!(msg.len(), HEADER_LEN + 4 + 2 + VERSION.len());
+        assert_eq!(&msg[0..4], &(msg.len() as u32).to_le_bytes());
+        assert_eq!(msg[4], TVERSION);
+        assert_eq!(&msg[5..7], &[0xff, 0xff]);
+        assert_eq!(&msg[7..11], &8192u32.to_le_bytes());
+        assert_eq!(&msg[11..13], &8u16.to_le_bytes());
+        assert_eq!(&msg[13..], b"9P2000.L");
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        // Rwalk with one qid
+        let reply = Encoder::new(TWALK + 1, 1)
+            .u16(1)
+            .u8(QTDIR)
+            .u32(3)
+            .u64(42)
+            .finish();
+        let mut body = parse_reply(&reply, TWALK + 1, 1).unwrap();
+        assert_eq!(body.u16().unwrap(), 1);
+        let qid = body.qid().unwrap();
+        assert!(qid.is_dir());
+        assert_eq!(qid.path, 42);
+
+        assert!(parse_reply(&reply, TREAD + 1, 1).is_err());
+        assert!(parse_reply(&reply, TWALK + 1, 2).is_err());
+        assert!(parse_reply(&reply[..10], TWALK + 1, 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_lerror() {
+        let reply = Encoder::new(RLERROR, 1).u32(2).finish();
+        assert_eq!(
+            parse_reply(&reply, TWALK + 1, 1).err(),
+            Some(KernelError::FsError(FsError::NotFound))
+        );
+        assert_eq!(
+            errno_error(39),
+            KernelError::FsError(FsError::DirectoryNotEmpty)
+        );
+        assert_eq!(errno_error(5), KernelError::FsError(FsError::IoError));
+    }
+
+    #[test]
+    fn test_parse_dirents() {
+        let mut data = Encoder::new(0, 0);
+        data.u8(QTDIR).u32(0).u64(1).u64(10).u8(4).str(".");
+        data.u8(0).u32(0).u64(7).u64(20).u8(8).str("notes.txt");
+        let data = data.finish();
+        let entries = parse_dirents(&data[HEADER_LEN..]).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, ".");
+        assert!(entries[0].qid.is_dir());
+        assert_eq!(entries[1].name, "notes.txt");
+        assert_eq!(entries[1].offset, 20);
+        assert_eq!(entries[1].qid.path, 7);
+
+        // A truncated entry is an error, not a short list
+        assert!(parse_dirents(&data[HEADER_LEN..data.len() - 1]).is_err());
+    }
+}
@@ -290,6 +290,10 @@ impl VfsNode for PipeReadNode {
             operation: "truncate pipe",
         })
     }
+
+    fn as_any(&self) -> Option<&dyn core::any::Any> {
+        Some(self)
+    }
 }
 
 /// VfsNode adapter wrapping the write end of a pipe.
@@ -380,12 +384,31 @@ impl VfsNode for PipeWriteNode {
             operation: "truncate pipe",
         })
     }
+
+    fn as_any(&self) -> Option<&dyn core::any::Any> {
+        Some(self)
+    }
+}
+
+/// Whether `node` is either end of a pipe.
+pub fn is_pipe_node(node: &dyn VfsNode) -> bool {
+    node.as_any()
+        .is_some_and(|any| any.is::<PipeReadNode>() || any.is::<PipeWriteNode>())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_pipe_node() {
+        let (reader, writer) = create_pipe().unwrap();
+        assert!(is_pipe_node(&PipeReadNode::new(reader)));
+        assert!(is_pipe_node(&PipeWriteNode::new(writer)));
+        let root = crate::fs::ramfs::RamFs::new();
+        assert!(!is_pipe_node(&*crate::fs::Filesystem::root(&root)));
+    }
+
     #[test]
     fn test_pipe_basic_read_write() {
         let (reader, writer) = create_pipe().unwrap();
@@ -9,7 +9,10 @@
 use alloc::{format, string::String, sync::Arc, vec::Vec};
 
 use super::{DirEntry, Filesystem, Metadata, NodeType, Permissions, VfsNode};
-use crate::error::{FsError, KernelError};
+use crate::{
+    error::{FsError, KernelError},
+    process::itimer::us_to_clock_ticks,
+};
 
 /// ProcFS node types
 enum ProcNodeType {
@@ -101,8 +104,13 @@ impl VfsNode for ProcNode {
                             used_kb,
                         )
                     }
+                    "veridian-release" => String::from(crate::utils::version::SBOM),
+                    "limits" => crate::limits::format_system_limits(),
+                    "cmdline" => format!("{}\n", crate::utils::cmdline::get()),
                     "cpuinfo" => generate_cpuinfo(),
                     "loadavg" => generate_loadavg(),
+                    "stat" => generate_stat(),
+                    "ksm" => crate::mm::ksm::report(),
                     _ => String::new(),
                 }
             }
@@ -130,14 +138,69 @@ impl VfsNode for ProcNode {
 
                             let parent = process.parent.unwrap_or(crate::process::ProcessId(0));
 
-                            format!(
+                            let creds = process.credentials();
+                            let groups: Vec<String> =
+                                creds.groups().iter().map(|g| format!("{}", g)).collect();
+
+                            let mut status = format!(
                                 "Name:\t{}\nPid:\t{}\nPPid:\t{}\nState:\t{}\n",
                                 name, pid, parent.0, state
-                            )
+                            );
+                            status.push_str(&format!(
+                                "Uid:\t{}\t{}\t{}\nGid:\t{}\t{}\t{}\nGroups:\t{}\n",
+                                creds.ruid,
+                                creds.euid,
+                                creds.suid,
+                                creds.rgid,
+                                creds.egid,
+                                creds.sgid,
+                                groups.join(" ")
+                            ));
+                            {
+                                let vas = process.memory_space.lock();
+                                status.push_str(&format!(
+                                    "VmSize:\t{} kB\nVmRSS:\t{} kB\n",
+                                    vas.get_stats().total_size / 1024,
+                                    crate::mm::maps::resident(&vas) / 1024
+                                ));
+                            }
+                            status.push_str(&format!(
+                                "SyscallFilters:\t{}\n",
+                                process.syscall_filters.lock().active.len()
+                            ));
+                            status
                         } else {
                             format!("Name:\tProcess\nPid:\t{}\nState:\tR (running)\n", pid)
                         }
                     }
+                    "caps" => {
+                        // Same rule as sys_cap_inspect: only the process
+                        // itself and uid 0 may see its capability space
+                        if let Some(caller) = crate::process::current_process() {
+                            if caller.pid.0 != *pid && caller.euid() != 0 {
+                                return Err(KernelError::PermissionDenied {
+                                    operation: "read /proc/<pid>/caps",
+                                });
+                            }
+                        }
+                        if let Some(process) =
+                            crate::process::get_process(crate::process::ProcessId(*pid))
+                        {
+                            let cap_space = process.capability_space.lock();
+                            crate::security::cap_audit::format_capabilities(&cap_space)
+                        } else {
+                            String::new()
+                        }
+                    }
+                    "limits" => {
+                        if let Some(process) =
+                            crate::process::get_process(crate::process::ProcessId(*pid))
+                        {
+                            crate::limits::format_process_limits(process)
+                        } else {
+                            String::new()
+                        }
+                    }
                     "cmdline" => {
                         // Get actual command line
                         if let Some(process) =
@@ -152,6 +215,38 @@ impl VfsNode for ProcNode {
                             format!("init\0")
                         }
                     }
+                    "stat" => {
+                        if let Some(process) =
+                            crate::process::get_process(crate::process::ProcessId(*pid))
+                        {
+                            generate_process_stat(process)
+                        } else {
+                            String::new()
+                        }
+                    }
+                    "heap" => {
+                        if let Some(process) =
+                            crate::process::get_process(crate::process::ProcessId(*pid))
+                        {
+                            crate::mm::heap_profile::process_report(process)
+                        } else {
+                            String::new()
+                        }
+                    }
+                    "maps" | "smaps" => {
+                        if let Some(process) =
+                            crate::process::get_process(crate::process::ProcessId(*pid))
+                        {
+                            let areas = crate::mm::maps::process_areas(process);
+                            if name == "maps" {
+                                crate::mm::maps::maps(&areas)
+                            } else {
+                                crate::mm::maps::smaps(&areas)
+                            }
+                        } else {
+                            String::new()
+                        }
+                    }
                     _ => String::new(),
                 }
             }
@@ -239,6 +334,36 @@ impl VfsNode for ProcNode {
                     inode: 0,
                 });
 
+                entries.push(DirEntry {
+                    name: String::from("veridian-release"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("limits"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("cmdline"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("stat"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("ksm"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
                 // Add process directories for all running processes
                 if let Some(process_list) = crate::process::get_process_list() {
                     for pid in process_list {
@@ -269,6 +394,42 @@ impl VfsNode for ProcNode {
                     node_type: NodeType::File,
                     inode: 0,
                 });
+
+                entries.push(DirEntry {
+                    name: String::from("caps"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("limits"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("stat"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("heap"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("maps"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
+
+                entries.push(DirEntry {
+                    name: String::from("smaps"),
+                    node_type: NodeType::File,
+                    inode: 0,
+                });
             }
             _ => return Err(KernelError::FsError(FsError::NotADirectory)),
         }
@@ -281,7 +442,8 @@ impl VfsNode for ProcNode {
             ProcNodeType::Root => {
                 // Check for system files
                 match name {
-                    "version" | "uptime" | "meminfo" | "cpuinfo" | "loadavg" => {
+                    "version" | "uptime" | "meminfo" | "cpuinfo" | "loadavg"
+                    | "veridian-release" | "limits" | "cmdline" | "stat" | "ksm" => {
                         Ok(Arc::new(ProcNode::new_system_file(String::from(name)))
                             as Arc<dyn VfsNode>)
                     }
@@ -302,10 +464,12 @@ impl VfsNode for ProcNode {
                 }
             }
             ProcNodeType::ProcessDir(pid) => match name {
-                "status" | "cmdline" => Ok(Arc::new(ProcNode::new_process_file(
-                    *pid,
-                    String::from(name),
-                )) as Arc<dyn VfsNode>),
+                "status" | "cmdline" | "caps" | "limits" | "stat" | "heap" | "maps" | "smaps" => {
+                    Ok(
+                        Arc::new(ProcNode::new_process_file(*pid, String::from(name)))
+                            as Arc<dyn VfsNode>,
+                    )
+                }
                 _ => Err(KernelError::FsError(FsError::NotFound)),
             },
             _ => Err(KernelError::FsError(FsError::NotADirectory)),
@@ -370,6 +534,125 @@ fn generate_cpuinfo() -> String {
     }
 }
 
+/// Generate /proc/stat content.
+///
+/// `cpu` lines in clock ticks: user, nice, system, idle, first summed over
+/// all CPUs and then for each CPU. The times come from the per-CPU totals
+/// of the tick accounting, so they include processes that have exited and
+/// never go backwards. A kernel without tick accounting reports its whole
+/// uptime as idle time on `cpu0`.
+fn generate_stat() -> String {
+    use crate::process::itimer::{cpu_times, CpuTimes};
+
+    let mut running = 0usize;
+    let pids = crate::process::get_process_list().unwrap_or_default();
+    for pid in &pids {
+        if let Some(p) = crate::process::get_process(crate::process::ProcessId(*pid)) {
+            if matches!(
+                p.get_state(),
+                crate::process::ProcessState::Running | crate::process::ProcessState::Ready
+            ) {
+                running += 1;
+            }
+        }
+    }
+
+    let mut cpus: Vec<(usize, CpuTimes)> = (0..crate::sched::smp::MAX_CPUS)
+        .filter_map(|cpu| Some((cpu, cpu_times(cpu)?)))
+        .collect();
+    if cpus.is_empty() {
+        let uptime_us = crate::arch::timer::get_timestamp_ms() * 1000;
+        cpus.push((
+            0,
+            CpuTimes {
+                idle_us: uptime_us,
+                ..CpuTimes::default()
+            },
+        ));
+    }
+    let line = |name: &str, t: &CpuTimes| {
+        format!(
+            "{name} {} 0 {} {}\n",
+            us_to_clock_ticks(t.user_us),
+            us_to_clock_ticks(t.system_us),
+            us_to_clock_ticks(t.idle_us)
+        )
+    };
+
+    let total = cpus
+        .iter()
+        .fold(CpuTimes::default(), |sum, (_, t)| CpuTimes {
+            user_us: sum.user_us + t.user_us,
+            system_us: sum.system_us + t.system_us,
+            idle_us: sum.idle_us + t.idle_us,
+        });
+    let mut out = line("cpu ", &total);
+    for (cpu, times) in &cpus {
+        out.push_str(&line(&format!("cpu{cpu}"), times));
+    }
+    out.push_str(&format!(
+        "processes {}\nprocs_running {}\n",
+        pids.len(),
+        running
+    ));
+    out
+}
+
+/// Generate /proc/<pid>/stat content.
+///
+/// The first 24 fields of the Linux layout: pid (comm) state ppid pgrp
+/// session tty_nr tpgid flags minflt cminflt majflt cmajflt utime stime
+/// cutime cstime priority nice num_threads itrealvalue starttime vsize rss.
+/// CPU times are in clock ticks, `starttime` in kernel timer ticks, `vsize`
+/// in bytes and `rss` in pages. Fields the kernel does not track are 0.
+fn generate_process_stat(process: &crate::process::Process) -> String {
+    use core::sync::atomic::Ordering;
+
+    use crate::process::{ProcessPriority, ProcessState};
+
+    let state = match process.get_state() {
+        ProcessState::Creating | ProcessState::Ready | ProcessState::Running => 'R',
+        ProcessState::Blocked => 'D',
+        ProcessState::Sleeping => 'S',
+        ProcessState::Zombie => 'Z',
+        ProcessState::Dead => 'X',
+    };
+    // Same scale as getpriority(); nice is the libc view of it
+    let (priority, nice) = match *process.priority.lock() {
+        ProcessPriority::RealTime => (20, -20),
+        ProcessPriority::System => (60, -20),
+        ProcessPriority::Normal => (100, 0),
+        ProcessPriority::Low => (130, 15),
+        ProcessPriority::Idle => (140, 19),
+    };
+    let ticks = |t: &core::sync::atomic::AtomicU64| us_to_clock_ticks(t.load(Ordering::Relaxed));
+
+    #[cfg(feature = "alloc")]
+    let (name, threads) = (&process.name, process.threads.lock().len());
+    #[cfg(not(feature = "alloc"))]
+    let (name, threads) = ("process", 1);
+
+    format!(
+        "{} ({}) {} {} {} {} 0 0 0 0 0 0 0 {} {} {} {} {} {} {} 0 {} {} {}\n",
+        process.pid.0,
+        name,
+        state,
+        process.parent.map_or(0, |p| p.0),
+        process.pgid.load(Ordering::Relaxed),
+        process.sid.load(Ordering::Relaxed),
+        ticks(&process.user_time),
+        ticks(&process.system_time),
+        ticks(&process.children_user_time),
+        ticks(&process.children_system_time),
+        priority,
+        nice,
+        threads,
+        process.created_at,
+        process.memory_stats.virtual_size.load(Ordering::Relaxed),
+        crate::mm::maps::resident(&process.memory_space.lock()) / 4096,
+    )
+}
+
 /// Generate /proc/loadavg content.
 fn generate_loadavg() -> String {
     // Count running/total tasks from process table
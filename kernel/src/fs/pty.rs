@@ -24,11 +24,10 @@
 use alloc::{collections::VecDeque, format, sync::Arc, vec::Vec};
 use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
-#[cfg(not(target_arch = "aarch64"))]
-use spin::RwLock;
-
 #[cfg(target_arch = "aarch64")]
 use super::bare_lock::RwLock;
+#[cfg(not(target_arch = "aarch64"))]
+use crate::sync::lockdep::RwLock;
 use crate::{error::KernelError, process::ProcessId, sync::once_lock::GlobalState};
 
 /// PTY buffer size
@@ -187,6 +186,19 @@ impl PtyMaster {
         Ok(data.len())
     }
 
+    /// Poll readiness of the master side: readable once the slave has
+    /// written output, writable while the slave's input has room.
+    pub fn poll_readiness(&self) -> u16 {
+        let mut events = 0u16;
+        if !self.output_buffer.read().is_empty() {
+            events |= 0x0001; // POLLIN
+        }
+        if self.input_buffer.read().len() < PTY_BUFFER_SIZE {
+            events |= 0x0004; // POLLOUT
+        }
+        events
+    }
+
     /// Send a signal to all processes in the foreground process group.
     ///
     /// Falls back to the controlling process if no foreground group is set.
@@ -363,6 +375,23 @@ impl PtySlave {
             })
         }
     }
+
+    /// Poll readiness of the slave side: readable once the master has
+    /// written input, writable while the master's output has room, hung up
+    /// once the master is gone.
+    pub fn poll_readiness(&self) -> u16 {
+        let Some(master) = get_pty_master(self.master_id) else {
+            return 0x0010; // POLLHUP
+        };
+        let mut events = 0u16;
+        if !master.input_buffer.read().is_empty() {
+            events |= 0x0001; // POLLIN
+        }
+        if master.output_buffer.read().len() < PTY_BUFFER_SIZE {
+            events |= 0x0004; // POLLOUT
+        }
+        events
+    }
 }
 
 /// PTY Manager for creating and managing PTY pairs
@@ -507,6 +536,10 @@ impl VfsNode for PtyMasterNode {
         self.master.write(data)
     }
 
+    fn poll_readiness(&self) -> u16 {
+        self.master.poll_readiness()
+    }
+
     fn metadata(&self) -> Result<Metadata, KernelError> {
         Ok(Metadata {
             node_type: NodeType::CharDevice,
@@ -600,6 +633,10 @@ impl VfsNode for PtySlaveNode {
         self.slave.write(data)
     }
 
+    fn poll_readiness(&self) -> u16 {
+        self.slave.poll_readiness()
+    }
+
     fn metadata(&self) -> Result<Metadata, KernelError> {
         Ok(Metadata {
             node_type: NodeType::CharDevice,
@@ -689,4 +726,13 @@ mod tests {
         assert_eq!(retrieved.rows, 30);
         assert_eq!(retrieved.cols, 100);
     }
+
+    #[test]
+    fn test_master_poll_readiness() {
+        let master = PtyMaster::new(0);
+        assert_eq!(master.poll_readiness(), 0x0004);
+
+        master.output_buffer.write().push_back(b'$');
+        assert_eq!(master.poll_readiness(), 0x0001 | 0x0004);
+    }
 }
@@ -83,7 +83,7 @@ fn is_zero_block(block: &[u8]) -> bool {
 ///
 /// For example, given `/usr/local/bin`, this will create `/usr`, `/usr/local`,
 /// and `/usr/local/bin` as directories if they don't already exist.
-fn ensure_parent_dirs(path: &str) -> Result<(), KernelError> {
+pub(super) fn ensure_parent_dirs(path: &str) -> Result<(), KernelError> {
     let vfs = get_vfs().read();
 
     let mut accumulated = String::new();
@@ -369,7 +369,7 @@ pub fn load_tar_to_vfs(data: &[u8]) -> Result<usize, KernelError> {
 /// Split a path into (parent, name).
 ///
 /// Returns `("/", "foo")` for `/foo`, or `("/a/b", "c")` for `/a/b/c`.
-fn split_path(path: &str) -> Result<(&str, &str), KernelError> {
+pub(super) fn split_path(path: &str) -> Result<(&str, &str), KernelError> {
     if let Some(pos) = path.rfind('/') {
         let parent = if pos == 0 { "/" } else { &path[..pos] };
         let name = &path[pos + 1..];
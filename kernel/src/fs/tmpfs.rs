@@ -439,6 +439,14 @@ impl VfsNode for TmpNode {
         metadata.modified = crate::arch::timer::get_timestamp_secs();
         Ok(())
     }
+
+    fn chown(&self, uid: u32, gid: u32) -> Result<(), KernelError> {
+        let mut metadata = self.metadata.write();
+        metadata.uid = uid;
+        metadata.gid = gid;
+        metadata.modified = crate::arch::timer::get_timestamp_secs();
+        Ok(())
+    }
 }
 
 /// tmpfs filesystem instance
@@ -3,10 +3,13 @@
 //! Provides POSIX-compatible extended file attributes with namespace support.
 //! Attributes are stored in-memory (suitable for RamFS/tmpfs) using a global
 //! store keyed by inode number. Each attribute has a namespaced name (e.g.,
-//! "user.mime_type" or "system.selinux") and an arbitrary byte value.
+//! "user.mime_type" or "security.capability") and an arbitrary byte value.
 //!
 //! Syscall-level functions: [`getxattr`], [`setxattr`], [`listxattr`],
 //! [`removexattr`]. Call [`cleanup_inode_xattrs`] when an inode is deleted.
+//!
+//! This store backs the default `VfsNode` xattr methods; filesystems with
+//! on-disk attribute storage (BlockFS) override those methods instead.
 
 use alloc::{collections::BTreeMap, string::String, vec::Vec};
 
@@ -67,11 +70,11 @@ static XATTR_STORE: RwLock<XattrStore> = RwLock::new(XattrStore::new());
 // ---------------------------------------------------------------------------
 
 /// Recognised xattr namespace prefixes.
-const VALID_NAMESPACES: &[&str] = &["user.", "system."];
+const VALID_NAMESPACES: &[&str] = &["user.", "system.", "security.", "trusted."];
 
 /// Validate that `name` begins with a supported namespace prefix and is
 /// otherwise well-formed.
-fn validate_name(name: &str) -> Result<(), KernelError> {
+pub(crate) fn validate_name(name: &str) -> Result<(), KernelError> {
     if name.is_empty() {
         return Err(KernelError::InvalidArgument {
             name: "xattr_name",
@@ -255,8 +258,14 @@ mod tests {
 
     #[test]
     fn test_validate_name_bad_ns() {
-        assert!(validate_name("trusted.key").is_err());
-        assert!(validate_name("security.ima").is_err());
+        assert!(validate_name("os2.key").is_err());
+        assert!(validate_name("ima").is_err());
+    }
+
+    #[test]
+    fn test_validate_name_security_ns() {
+        assert!(validate_name("security.capability").is_ok());
+        assert!(validate_name("trusted.overlay").is_ok());
     }
 
     #[test]
@@ -366,7 +375,7 @@ mod tests {
     #[test]
     fn test_set_invalid_namespace() {
         let inode = BASE_INODE + 7;
-        let result = setxattr(inode, "trusted.secret", b"val", 0);
+        let result = setxattr(inode, "bogus.secret", b"val", 0);
         assert!(result.is_err());
     }
 
@@ -1,13 +1,44 @@
-//! Hardware cursor sprite rendering.
+//! Mouse cursor images and the cursor plane.
 //!
-//! Provides a 16x16 arrow cursor bitmap and a compositing function
-//! to overlay the cursor onto the framebuffer back-buffer.
+//! The cursor is kept out of the composited scene so pointer motion never
+//! forces a recomposite:
+//!
+//! - When the virtio-gpu device has a cursor queue, the image is uploaded once
+//!   and each motion is a single MOVE_CURSOR command.
+//! - Otherwise the cursor is alpha-blended straight into the framebuffer. The
+//!   pixels underneath are saved, so a move only restores one small rectangle
+//!   and blends another; both are reported as damage.
+//!
+//! Images are read from Xcursor theme files
+//! (`/usr/share/icons/<theme>/cursors/<name>`) with a built-in 16x16 arrow
+//! as the fallback. Each image carries its hot spot, the pixel that sits
+//! exactly under the pointer position.
+
+use alloc::{format, string::String, vec::Vec};
 
-/// Cursor width in pixels.
+use super::damage_tracking::DamageRect;
+use crate::error::KernelError;
+
+/// Built-in arrow width in pixels.
 pub const CURSOR_WIDTH: usize = 16;
-/// Cursor height in pixels.
+/// Built-in arrow height in pixels.
 pub const CURSOR_HEIGHT: usize = 16;
 
+/// Theme used when none is configured.
+pub const DEFAULT_THEME: &str = "default";
+/// Nominal cursor size requested from theme files.
+pub const DEFAULT_SIZE: u32 = 24;
+
+/// Largest image accepted from a theme file (each side, in pixels).
+const MAX_IMAGE_DIM: u32 = 256;
+
+/// Xcursor file magic.
+const XCURSOR_MAGIC: &[u8; 4] = b"Xcur";
+/// Xcursor table-of-contents type for image chunks.
+const XCURSOR_IMAGE_TYPE: u32 = 0xfffd_0002;
+/// Size of the Xcursor image chunk header.
+const XCURSOR_IMAGE_HEADER: usize = 36;
+
 /// 16x16 arrow cursor bitmap.
 ///
 /// Each row is a u16 where bit 15 = leftmost pixel.
@@ -52,58 +83,590 @@ const CURSOR_MASK: [u16; CURSOR_HEIGHT] = [
     0b0000_0001_1100_0000,
 ];
 
-/// Draw the cursor sprite onto a pixel buffer.
-///
-/// # Arguments
-/// - `buf`: Pixel buffer (BGRA format, 4 bytes/pixel).
-/// - `stride`: Buffer stride in bytes.
-/// - `buf_width`: Buffer width in pixels.
-/// - `buf_height`: Buffer height in pixels.
-/// - `cx`, `cy`: Cursor position (top-left of sprite).
-pub fn draw_cursor(
-    buf: &mut [u8],
-    stride: usize,
-    buf_width: usize,
-    buf_height: usize,
-    cx: i32,
-    cy: i32,
-) {
-    for row in 0..CURSOR_HEIGHT {
-        let py = cy as usize + row;
-        if py >= buf_height {
-            break;
-        }
-        let mask_bits = CURSOR_MASK[row];
-        let data_bits = CURSOR_DATA[row];
-
-        for col in 0..CURSOR_WIDTH {
-            let px = cx as usize + col;
-            if px >= buf_width {
-                break;
+// ---------------------------------------------------------------------------
+// Cursor images
+// ---------------------------------------------------------------------------
+
+/// Cursor shapes the window manager asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    /// Normal pointer.
+    Default,
+    /// A window is being moved.
+    Move,
+}
+
+impl CursorShape {
+    /// Xcursor file name of this shape within a theme.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "left_ptr",
+            Self::Move => "fleur",
+        }
+    }
+}
+
+/// A cursor image with its hot spot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Hot spot column (the pixel under the pointer position).
+    pub hot_x: u32,
+    /// Hot spot row.
+    pub hot_y: u32,
+    /// Premultiplied ARGB pixels, row-major (`width * height` words).
+    pub pixels: Vec<u32>,
+}
+
+impl CursorImage {
+    /// The built-in 16x16 arrow, hot spot at its tip.
+    pub fn arrow() -> Self {
+        let mut pixels = Vec::with_capacity(CURSOR_WIDTH * CURSOR_HEIGHT);
+        for row in 0..CURSOR_HEIGHT {
+            for col in 0..CURSOR_WIDTH {
+                let bit = 15 - col;
+                let in_mask = (CURSOR_MASK[row] >> bit) & 1 != 0;
+                let in_data = (CURSOR_DATA[row] >> bit) & 1 != 0;
+                pixels.push(match (in_mask, in_data) {
+                    (true, true) => 0xFFFF_FFFF,
+                    (true, false) => 0xFF00_0000,
+                    _ => 0,
+                });
             }
+        }
+        Self {
+            width: CURSOR_WIDTH as u32,
+            height: CURSOR_HEIGHT as u32,
+            hot_x: 0,
+            hot_y: 0,
+            pixels,
+        }
+    }
 
-            let bit = 15 - col;
-            let in_mask = (mask_bits >> bit) & 1 != 0;
-            let in_data = (data_bits >> bit) & 1 != 0;
-
-            if in_mask {
-                let offset = py * stride + px * 4;
-                if offset + 3 < buf.len() {
-                    if in_data {
-                        // White foreground
-                        buf[offset] = 0xFF;
-                        buf[offset + 1] = 0xFF;
-                        buf[offset + 2] = 0xFF;
-                        buf[offset + 3] = 0xFF;
-                    } else {
-                        // Black outline
-                        buf[offset] = 0x00;
-                        buf[offset + 1] = 0x00;
-                        buf[offset + 2] = 0x00;
-                        buf[offset + 3] = 0xFF;
-                    }
+    /// Parse an Xcursor file, picking the image whose nominal size is
+    /// closest to `size`.
+    ///
+    /// Animated cursors contribute only their first frame.
+    pub fn from_xcursor(data: &[u8], size: u32) -> Result<Self, KernelError> {
+        if data.len() < 16 || &data[0..4] != XCURSOR_MAGIC {
+            return Err(KernelError::InvalidArgument {
+                name: "xcursor",
+                value: "bad_magic",
+            });
+        }
+        let header_len = read_u32(data, 4)? as usize;
+        let ntoc = read_u32(data, 12)? as usize;
+
+        // Nearest nominal size wins; ties keep the first (first frame).
+        let mut best: Option<(u32, usize)> = None;
+        for i in 0..ntoc {
+            let entry = header_len + i * 12;
+            if read_u32(data, entry)? != XCURSOR_IMAGE_TYPE {
+                continue;
+            }
+            let distance = read_u32(data, entry + 4)?.abs_diff(size);
+            let position = read_u32(data, entry + 8)? as usize;
+            if best.is_none_or(|(d, _)| distance < d) {
+                best = Some((distance, position));
+            }
+        }
+        let Some((_, pos)) = best else {
+            return Err(KernelError::NotFound {
+                resource: "xcursor_image",
+                id: size as u64,
+            });
+        };
+
+        if read_u32(data, pos)? as usize != XCURSOR_IMAGE_HEADER
+            || read_u32(data, pos + 4)? != XCURSOR_IMAGE_TYPE
+        {
+            return Err(KernelError::InvalidArgument {
+                name: "xcursor",
+                value: "bad_image_header",
+            });
+        }
+        let width = read_u32(data, pos + 16)?;
+        let height = read_u32(data, pos + 20)?;
+        let hot_x = read_u32(data, pos + 24)?;
+        let hot_y = read_u32(data, pos + 28)?;
+        if width == 0
+            || height == 0
+            || width > MAX_IMAGE_DIM
+            || height > MAX_IMAGE_DIM
+            || hot_x >= width
+            || hot_y >= height
+        {
+            return Err(KernelError::InvalidArgument {
+                name: "xcursor",
+                value: "bad_image_size",
+            });
+        }
+
+        let count = (width * height) as usize;
+        let start = pos + XCURSOR_IMAGE_HEADER;
+        let mut pixels = Vec::with_capacity(count);
+        for i in 0..count {
+            pixels.push(read_u32(data, start + i * 4)?);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            hot_x,
+            hot_y,
+            pixels,
+        })
+    }
+
+    /// Load `shape` from the Xcursor theme `theme`.
+    pub fn load(theme: &str, shape: CursorShape, size: u32) -> Result<Self, KernelError> {
+        if crate::fs::try_get_vfs().is_none() {
+            return Err(KernelError::InvalidState {
+                expected: "filesystem mounted",
+                actual: "no filesystem",
+            });
+        }
+        let path = format!("/usr/share/icons/{}/cursors/{}", theme, shape.name());
+        let data = crate::fs::read_file(&path)?;
+        Self::from_xcursor(&data, size)
+    }
+}
+
+/// Read a little-endian u32, failing on truncated input.
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, KernelError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(KernelError::InvalidArgument {
+            name: "xcursor",
+            value: "truncated",
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Cursor plane
+// ---------------------------------------------------------------------------
+
+/// Framebuffer the software cursor is blended into.
+pub struct CursorTarget<'a> {
+    /// Pixel memory (4 bytes/pixel).
+    pub buf: &'a mut [u8],
+    /// Row stride in bytes.
+    pub stride: usize,
+    /// Width in pixels.
+    pub width: usize,
+    /// Height in pixels.
+    pub height: usize,
+    /// Pixels are stored B, G, R, X (otherwise R, G, B, X).
+    pub is_bgr: bool,
+}
+
+impl CursorTarget<'_> {
+    fn offset(&self, x: usize, y: usize) -> Option<usize> {
+        let offset = y * self.stride + x * 4;
+        (offset + 4 <= self.buf.len()).then_some(offset)
+    }
+
+    fn read(&self, x: usize, y: usize) -> u32 {
+        self.offset(x, y).map_or(0, |o| {
+            u32::from_le_bytes([
+                self.buf[o],
+                self.buf[o + 1],
+                self.buf[o + 2],
+                self.buf[o + 3],
+            ])
+        })
+    }
+
+    fn write(&mut self, x: usize, y: usize, pixel: u32) {
+        if let Some(o) = self.offset(x, y) {
+            self.buf[o..o + 4].copy_from_slice(&pixel.to_le_bytes());
+        }
+    }
+}
+
+/// Framebuffer pixels covered by the software cursor, kept for restoring.
+struct SavedUnder {
+    rect: DamageRect,
+    pixels: Vec<u32>,
+}
+
+/// The cursor plane: current shape, position, and how it is shown.
+pub struct CursorPlane {
+    /// Xcursor theme directory name.
+    theme: String,
+    /// Nominal size requested from the theme.
+    size: u32,
+    /// Shape currently shown.
+    shape: CursorShape,
+    /// Image for `shape`.
+    image: CursorImage,
+    /// Images loaded so far (theme file or built-in fallback).
+    images: Vec<(CursorShape, CursorImage)>,
+    /// Pointer (hot spot) position.
+    x: i32,
+    y: i32,
+    /// The GPU cursor plane shows the cursor.
+    hardware: bool,
+    /// The image changed since it was last shown.
+    image_dirty: bool,
+    /// Software cursor: the framebuffer area it covers and what was there.
+    saved: Option<SavedUnder>,
+}
+
+impl CursorPlane {
+    /// Create a software cursor plane using `theme` at `size`.
+    pub fn new(theme: &str, size: u32) -> Self {
+        let mut plane = Self {
+            theme: String::from(theme),
+            size,
+            shape: CursorShape::Default,
+            image: CursorImage::arrow(),
+            images: Vec::new(),
+            x: 0,
+            y: 0,
+            hardware: false,
+            image_dirty: true,
+            saved: None,
+        };
+        plane.image = plane.load(CursorShape::Default);
+        plane
+    }
+
+    /// Switch to another Xcursor theme; shapes are reloaded on demand.
+    pub fn set_theme(&mut self, theme: &str, size: u32) {
+        self.theme = String::from(theme);
+        self.size = size;
+        self.images.clear();
+        self.image = self.load(self.shape);
+        self.image_dirty = true;
+    }
+
+    /// Change the cursor shape.
+    pub fn set_shape(&mut self, shape: CursorShape) {
+        if shape != self.shape {
+            self.image = self.load(shape);
+            self.shape = shape;
+            self.image_dirty = true;
+        }
+    }
+
+    /// Shape currently shown.
+    pub fn shape(&self) -> CursorShape {
+        self.shape
+    }
+
+    /// Use the GPU cursor plane (when `enable` and the device has one).
+    ///
+    /// Falls back to the software cursor if a later GPU command fails.
+    pub fn set_hardware(&mut self, enable: bool) {
+        if enable != self.hardware {
+            self.hardware = enable;
+            self.image_dirty = true;
+        }
+    }
+
+    /// Whether the GPU cursor plane shows the cursor.
+    pub fn is_hardware(&self) -> bool {
+        self.hardware
+    }
+
+    /// Image for `shape`, read from the theme on first use.
+    fn load(&mut self, shape: CursorShape) -> CursorImage {
+        if let Some((_, image)) = self.images.iter().find(|(s, _)| *s == shape) {
+            return image.clone();
+        }
+        let image = CursorImage::load(&self.theme, shape, self.size)
+            .unwrap_or_else(|_| CursorImage::arrow());
+        self.images.push((shape, image.clone()));
+        image
+    }
+
+    /// Show the cursor with its hot spot at `(x, y)` for this frame.
+    ///
+    /// `redrawn` means the framebuffer under the cursor was repainted since
+    /// the last call: the software cursor is gone from it and the saved
+    /// pixels are stale. Returns the framebuffer areas the software cursor
+    /// changed; empty when nothing changed or the GPU plane shows it.
+    pub(crate) fn present(
+        &mut self,
+        x: i32,
+        y: i32,
+        fb: &mut CursorTarget<'_>,
+        redrawn: bool,
+    ) -> Vec<DamageRect> {
+        let moved = (x, y) != (self.x, self.y);
+        self.x = x;
+        self.y = y;
+
+        if self.hardware {
+            if !moved && !self.image_dirty {
+                return Vec::new();
+            }
+            if self.present_hardware() {
+                self.image_dirty = false;
+                return Vec::new();
+            }
+            // The GPU rejected the cursor; draw it ourselves from now on.
+            self.hardware = false;
+        }
+
+        if redrawn {
+            self.saved = None;
+        } else if !moved && !self.image_dirty && self.saved.is_some() {
+            return Vec::new();
+        }
+
+        let mut damage = Vec::new();
+        if let Some(saved) = self.saved.take() {
+            restore(fb, &saved);
+            damage.push(saved.rect);
+        }
+        if let Some(saved) = self.draw(fb) {
+            damage.push(saved.rect);
+            self.saved = Some(saved);
+        }
+        self.image_dirty = false;
+        damage
+    }
+
+    /// Upload or move the GPU cursor. Returns `false` if the GPU failed.
+    fn present_hardware(&self) -> bool {
+        let image = &self.image;
+        let x = self.x.max(0) as u32;
+        let y = self.y.max(0) as u32;
+        let upload = self.image_dirty;
+        let result = crate::drivers::virtio_gpu::with_driver(|gpu| {
+            if upload {
+                gpu.set_cursor_image(
+                    &image.pixels,
+                    image.width,
+                    image.height,
+                    image.hot_x,
+                    image.hot_y,
+                    x,
+                    y,
+                )
+            } else {
+                gpu.move_cursor(x, y)
+            }
+        });
+        matches!(result, Some(Ok(())))
+    }
+
+    /// Blend the cursor into `fb`, returning what it covered.
+    fn draw(&self, fb: &mut CursorTarget<'_>) -> Option<SavedUnder> {
+        let image = &self.image;
+        let left = self.x - image.hot_x as i32;
+        let top = self.y - image.hot_y as i32;
+        let bounds = DamageRect::new(0, 0, fb.width as u32, fb.height as u32);
+        let rect = DamageRect::new(left, top, image.width, image.height).clamp_to(&bounds);
+        if rect.is_empty() {
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity((rect.width * rect.height) as usize);
+        for py in rect.y..rect.bottom() {
+            for px in rect.x..rect.right() {
+                let (fx, fy) = (px as usize, py as usize);
+                let under = fb.read(fx, fy);
+                pixels.push(under);
+
+                let src =
+                    image.pixels[((py - top) as u32 * image.width + (px - left) as u32) as usize];
+                if src >> 24 == 0 {
+                    continue;
                 }
+                let src = if fb.is_bgr { src } else { swap_red_blue(src) };
+                fb.write(fx, fy, blend(src, under));
+            }
+        }
+        Some(SavedUnder { rect, pixels })
+    }
+}
+
+/// Put back the pixels the software cursor covered.
+fn restore(fb: &mut CursorTarget<'_>, saved: &SavedUnder) {
+    let mut pixels = saved.pixels.iter();
+    for py in saved.rect.y..saved.rect.bottom() {
+        for px in saved.rect.x..saved.rect.right() {
+            if let Some(&pixel) = pixels.next() {
+                fb.write(px as usize, py as usize, pixel);
+            }
+        }
+    }
+}
+
+/// Composite a premultiplied ARGB pixel over an opaque one.
+fn blend(src: u32, dst: u32) -> u32 {
+    let alpha = src >> 24;
+    if alpha == 0xFF {
+        return src | 0xFF00_0000;
+    }
+    let inv = 255 - alpha;
+    let mut out = 0xFF00_0000;
+    for shift in [0, 8, 16] {
+        let s = (src >> shift) & 0xFF;
+        let d = (dst >> shift) & 0xFF;
+        out |= (s + (d * inv + 127) / 255).min(255) << shift;
+    }
+    out
+}
+
+/// Convert between ARGB and ABGR channel order.
+fn swap_red_blue(pixel: u32) -> u32 {
+    (pixel & 0xFF00_FF00) | ((pixel >> 16) & 0xFF) | ((pixel & 0xFF) << 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    /// Build an Xcursor file with one image per `(nominal, w, h, hot_x,
+    /// hot_y, fill)` entry.
+    fn xcursor(images: &[(u32, u32, u32, u32, u32, u32)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let push = |out: &mut Vec<u8>, v: u32| out.extend_from_slice(&v.to_le_bytes());
+        out.extend_from_slice(XCURSOR_MAGIC);
+        push(&mut out, 16);
+        push(&mut out, 0x1_0000);
+        push(&mut out, images.len() as u32);
+
+        let mut pos = 16 + 12 * images.len() as u32;
+        for &(nominal, w, h, ..) in images {
+            push(&mut out, XCURSOR_IMAGE_TYPE);
+            push(&mut out, nominal);
+            push(&mut out, pos);
+            pos += XCURSOR_IMAGE_HEADER as u32 + 4 * w * h;
+        }
+        for &(nominal, w, h, hot_x, hot_y, fill) in images {
+            for v in [36, XCURSOR_IMAGE_TYPE, nominal, 1, w, h, hot_x, hot_y, 0] {
+                push(&mut out, v);
             }
+            for _ in 0..w * h {
+                push(&mut out, fill);
+            }
+        }
+        out
+    }
+
+    fn target(buf: &mut [u8], width: usize, height: usize) -> CursorTarget<'_> {
+        CursorTarget {
+            buf,
+            stride: width * 4,
+            width,
+            height,
+            is_bgr: true,
+        }
+    }
+
+    fn pixel(buf: &[u8], width: usize, x: usize, y: usize) -> u32 {
+        let o = (y * width + x) * 4;
+        u32::from_le_bytes([buf[o], buf[o + 1], buf[o + 2], buf[o + 3]])
+    }
+
+    #[test]
+    fn test_arrow_image() {
+        let arrow = CursorImage::arrow();
+        assert_eq!((arrow.width, arrow.height), (16, 16));
+        assert_eq!((arrow.hot_x, arrow.hot_y), (0, 0));
+        assert_eq!(arrow.pixels[0], 0xFFFF_FFFF); // tip is white
+        assert_eq!(arrow.pixels[1], 0xFF00_0000); // outline
+        assert_eq!(arrow.pixels[15], 0); // transparent
+    }
+
+    #[test]
+    fn test_xcursor_picks_nearest_size() {
+        let data = xcursor(&[(16, 2, 2, 1, 1, 0xFF11_1111), (32, 3, 3, 2, 0, 0xFF22_2222)]);
+        let small = CursorImage::from_xcursor(&data, 20).unwrap();
+        assert_eq!((small.width, small.hot_x, small.hot_y), (2, 1, 1));
+        assert_eq!(small.pixels, vec![0xFF11_1111; 4]);
+
+        let large = CursorImage::from_xcursor(&data, 30).unwrap();
+        assert_eq!((large.width, large.hot_x, large.hot_y), (3, 2, 0));
+    }
+
+    #[test]
+    fn test_xcursor_rejects_bad_files() {
+        assert!(CursorImage::from_xcursor(b"nope", 24).is_err());
+
+        let mut data = xcursor(&[(24, 4, 4, 0, 0, 0)]);
+        data.truncate(data.len() - 1);
+        assert!(CursorImage::from_xcursor(&data, 24).is_err());
+
+        // Hot spot outside the image
+        let data = xcursor(&[(24, 4, 4, 4, 0, 0)]);
+        assert!(CursorImage::from_xcursor(&data, 24).is_err());
+    }
+
+    #[test]
+    fn test_software_cursor_restores_background() {
+        let (w, h) = (32, 32);
+        let mut buf = vec![0u8; w * h * 4];
+        for (i, px) in buf.chunks_mut(4).enumerate() {
+            px.copy_from_slice(&(i as u32).to_le_bytes());
         }
+        let original = buf.clone();
+
+        let mut plane = CursorPlane::new("missing-theme", DEFAULT_SIZE);
+        let damage = plane.present(4, 4, &mut target(&mut buf, w, h), true);
+        assert_eq!(damage, vec![DamageRect::new(4, 4, 16, 16)]);
+        assert_eq!(pixel(&buf, w, 4, 4), 0xFFFF_FFFF);
+
+        // Same place, nothing repainted: nothing to do
+        assert!(plane
+            .present(4, 4, &mut target(&mut buf, w, h), false)
+            .is_empty());
+
+        // Moving restores the old area and clips the new one to the screen
+        let damage = plane.present(20, 20, &mut target(&mut buf, w, h), false);
+        assert_eq!(
+            damage,
+            vec![
+                DamageRect::new(4, 4, 16, 16),
+                DamageRect::new(20, 20, 12, 12)
+            ]
+        );
+        assert_eq!(pixel(&buf, w, 4, 4), pixel(&original, w, 4, 4));
+        assert_eq!(pixel(&buf, w, 20, 20), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn test_redrawn_frame_discards_saved_pixels() {
+        let (w, h) = (32, 32);
+        let mut buf = vec![0u8; w * h * 4];
+        let mut plane = CursorPlane::new("missing-theme", DEFAULT_SIZE);
+        plane.present(0, 0, &mut target(&mut buf, w, h), true);
+
+        // The compositor repainted the frame in a new color
+        buf.fill(0x40);
+        let damage = plane.present(10, 10, &mut target(&mut buf, w, h), true);
+        assert_eq!(damage, vec![DamageRect::new(10, 10, 16, 16)]);
+        assert_eq!(pixel(&buf, w, 0, 0), 0x4040_4040);
+    }
+
+    #[test]
+    fn test_hot_spot_offsets_image() {
+        let data = xcursor(&[(24, 4, 4, 2, 3, 0xFF00_FF00)]);
+        let mut plane = CursorPlane::new("missing-theme", DEFAULT_SIZE);
+        plane.image = CursorImage::from_xcursor(&data, 24).unwrap();
+
+        let (w, h) = (16, 16);
+        let mut buf = vec![0u8; w * h * 4];
+        let damage = plane.present(8, 8, &mut target(&mut buf, w, h), true);
+        assert_eq!(damage, vec![DamageRect::new(6, 5, 4, 4)]);
+        assert_eq!(pixel(&buf, w, 6, 5), 0xFF00_FF00);
+    }
+
+    #[test]
+    fn test_blend_premultiplied() {
+        assert_eq!(blend(0xFF12_3456, 0xFFFF_FFFF), 0xFF12_3456);
+        // 50% black over white
+        assert_eq!(blend(0x8000_0000, 0xFFFF_FFFF), 0xFF7F_7F7F);
+        assert_eq!(swap_red_blue(0xFF11_2233), 0xFF33_2211);
     }
 }
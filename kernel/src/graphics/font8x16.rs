@@ -9,12 +9,85 @@
 pub const FONT_WIDTH: usize = 8;
 pub const FONT_HEIGHT: usize = 16;
 
+/// Glyph drawn for characters the font has no shape for (a filled square).
+pub const MISSING_GLYPH: u8 = 0xFE;
+
 /// Return the 16-byte glyph bitmap for the given byte value.
 #[inline]
 pub fn glyph(ch: u8) -> &'static [u8; 16] {
     &FONT_8X16[ch as usize]
 }
 
+/// Unicode characters of the symbol glyphs at 0x01..=0x1F.
+const CP437_LOW: [char; 31] = [
+    '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', '►', '◄', '↕', '‼',
+    '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+];
+
+/// Unicode characters of the glyphs at 0x80..=0xFF.
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+/// Base letters of U+00C0..=U+017F (Latin-1 letters and Latin Extended-A),
+/// for accented letters the font does not have.
+const LATIN_BASE: &[u8; 192] = b"\
+AAAAAAACEEEEIIIIDNOOOOOxOUUUUYTsaaaaaaaceeeeiiiidnooooo/ouuuuyty\
+AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIiIiJjKkkLlLlLlL\
+lLlNnNnNnnNnOoOoOoOoRrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs";
+
+/// The glyph that best shows `ch`.
+///
+/// ASCII maps to itself. Other characters use the code page 437 glyph for
+/// the same character if there is one, then a look-alike: the base letter
+/// of an accented Latin letter, the Greek letter CP437 substitutes, or
+/// plain ASCII punctuation for typographic quotes and dashes. Anything
+/// else is [`MISSING_GLYPH`]. Widths are the caller's business: CJK
+/// characters have no glyph here and come back as [`MISSING_GLYPH`] too.
+pub fn glyph_index(ch: char) -> u8 {
+    if ch.is_ascii() && !ch.is_ascii_control() {
+        return ch as u8;
+    }
+    if let Some(i) = CP437_HIGH.iter().position(|&c| c == ch) {
+        return 0x80 + i as u8;
+    }
+    if let Some(i) = CP437_LOW.iter().position(|&c| c == ch) {
+        return 1 + i as u8;
+    }
+    match ch {
+        '\u{C0}'..='\u{17F}' => LATIN_BASE[ch as usize - 0xC0],
+        '⌂' => 0x7F,
+        'β' => 0xE1,
+        'μ' => 0xE6,
+        'Ω' => 0xEA,
+        'ϕ' | '∅' => 0xED,
+        '∈' | 'ϵ' => 0xEE,
+        '∑' => 0xE4,
+        '‘' | '’' | '‚' | '′' | '´' => b'\'',
+        '“' | '”' | '„' | '″' => b'"',
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => b'-',
+        '‹' => b'<',
+        '›' => b'>',
+        '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}' => b' ',
+        '━' => 0xC4,
+        '┃' => 0xB3,
+        '╭' | '┏' => 0xDA,
+        '╮' | '┓' => 0xBF,
+        '╰' | '┗' => 0xC0,
+        '╯' | '┛' => 0xD9,
+        '▪' | '◼' | '◾' => 0xFE,
+        '✓' | '✔' => 0xFB,
+        _ => MISSING_GLYPH,
+    }
+}
+
 /// VGA ROM 8x16 bitmap font data.
 ///
 /// 256 glyphs x 16 bytes each = 4096 bytes total.
@@ -1211,3 +1284,26 @@ pub(crate) static FONT_8X16: [[u8; 16]; 256] = [
         0x00,
     ], // 0xFF
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_index() {
+        assert_eq!(glyph_index('A'), b'A');
+        assert_eq!(glyph_index('é'), 0x82);
+        assert_eq!(glyph_index('\u{A0}'), 0xFF);
+        assert_eq!(glyph_index('♥'), 0x03);
+        assert_eq!(glyph_index('═'), 0xCD);
+        // Look-alikes
+        assert_eq!(glyph_index('ł'), b'l');
+        assert_eq!(glyph_index('Ž'), b'Z');
+        assert_eq!(glyph_index('’'), b'\'');
+        assert_eq!(glyph_index('μ'), 0xE6);
+        // No glyph
+        assert_eq!(glyph_index('€'), MISSING_GLYPH);
+        assert_eq!(glyph_index('中'), MISSING_GLYPH);
+        assert_eq!(glyph_index('\t'), MISSING_GLYPH);
+    }
+}
@@ -4,20 +4,33 @@
 
 use crate::error::KernelError;
 
+pub mod fbcon;
+pub mod font8x16;
+pub mod framebuffer;
+pub mod vsync_sw;
+
+// Compositor and GPU stack (the `graphics` feature); the console and
+// framebuffer above are all a headless build keeps.
+#[cfg(feature = "graphics")]
 pub mod compositor;
+#[cfg(feature = "graphics")]
 pub mod cursor;
+#[cfg(feature = "graphics")]
 pub mod damage_tracking;
+#[cfg(feature = "graphics")]
 pub mod drm_ioctl;
-pub mod fbcon;
-pub mod font8x16;
-pub mod framebuffer;
+#[cfg(feature = "graphics")]
 pub mod gl_compositor;
+#[cfg(feature = "graphics")]
 pub mod gpu;
+#[cfg(feature = "graphics")]
 pub mod gpu_accel;
+#[cfg(feature = "graphics")]
 pub mod multi_output;
+#[cfg(feature = "graphics")]
 pub mod shader;
+#[cfg(feature = "graphics")]
 pub mod texture_atlas;
-pub mod vsync_sw;
 
 /// Canonical pixel format descriptor.
 ///
@@ -153,6 +166,7 @@ pub fn init() -> Result<(), KernelError> {
     framebuffer::init()?;
 
     // Initialize compositor
+    #[cfg(feature = "graphics")]
     compositor::init()?;
 
     println!("[GFX] Graphics subsystem initialized");
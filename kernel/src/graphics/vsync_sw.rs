@@ -4,12 +4,17 @@
 //! for frame pacing at ~60Hz (16.667ms per frame). Coordinates double-buffer
 //! swap timing for smooth presentation without hardware VSync support.
 //!
+//! The timer tick also drives a vblank counter standing in for the display
+//! interrupt that virtio-gpu and linear framebuffers lack. The compositor
+//! and `SYS_FB_FLIP` wait on it with [`wait_for_vblank`], which yields the
+//! CPU, so frames are presented once per refresh instead of as fast as the
+//! render loop can spin.
+//!
 //! All timing uses integer nanoseconds (no FPU required).
 
 #![allow(dead_code)]
 
 use alloc::vec::Vec;
-#[cfg(not(target_arch = "x86_64"))]
 use core::sync::atomic::{AtomicU64, Ordering};
 
 use spin::Mutex;
@@ -384,6 +389,113 @@ pub(crate) fn with_sw_vsync<R, F: FnOnce(&mut SwVsyncState) -> R>(f: F) -> Optio
     SW_VSYNC.lock().as_mut().map(f)
 }
 
+// ---------------------------------------------------------------------------
+// Vblank clock
+// ---------------------------------------------------------------------------
+
+/// A software vblank: a counter that advances once per refresh interval.
+pub(crate) struct VblankClock {
+    /// Vblanks signalled so far.
+    seq: AtomicU64,
+    /// Deadline of the next vblank (ns); 0 until the first `advance`.
+    next_ns: AtomicU64,
+    /// Refresh interval (ns).
+    interval_ns: AtomicU64,
+}
+
+impl VblankClock {
+    pub(crate) const fn new(interval_ns: u64) -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            next_ns: AtomicU64::new(0),
+            interval_ns: AtomicU64::new(interval_ns),
+        }
+    }
+
+    /// Signal a vblank if its deadline has passed at `now_ns`. Returns
+    /// true if one was signalled.
+    ///
+    /// Missed deadlines are folded into a single vblank so a stalled
+    /// timer does not release waiters in a burst. Safe to call from
+    /// several CPUs at once; only one of them signals each vblank.
+    pub(crate) fn advance(&self, now_ns: u64) -> bool {
+        let interval = self.interval_ns.load(Ordering::Relaxed).max(1);
+        let next = self.next_ns.load(Ordering::Acquire);
+        if next == 0 {
+            let _ = self.next_ns.compare_exchange(
+                0,
+                now_ns + interval,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            );
+            return false;
+        }
+        if now_ns < next {
+            return false;
+        }
+
+        let missed = (now_ns - next) / interval;
+        let deadline = next + interval * (missed + 1);
+        if self
+            .next_ns
+            .compare_exchange(next, deadline, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        self.seq.fetch_add(1, Ordering::Release);
+        true
+    }
+
+    /// Vblanks signalled so far.
+    pub(crate) fn seq(&self) -> u64 {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    /// Change the refresh interval, starting with the next vblank.
+    pub(crate) fn set_interval_ns(&self, interval_ns: u64) {
+        if interval_ns > 0 {
+            self.interval_ns.store(interval_ns, Ordering::Relaxed);
+        }
+    }
+
+    /// Refresh interval in nanoseconds.
+    pub(crate) fn interval_ns(&self) -> u64 {
+        self.interval_ns.load(Ordering::Relaxed)
+    }
+}
+
+static VBLANK: VblankClock = VblankClock::new(VSYNC_INTERVAL_NS);
+
+/// Advance the vblank clock. Called from the timer tick.
+pub(crate) fn vblank_tick() {
+    VBLANK.advance(crate::arch::timer::get_timestamp_ns());
+}
+
+/// Vblanks signalled since boot.
+pub fn vblank_seq() -> u64 {
+    VBLANK.seq()
+}
+
+/// Yield the CPU until a vblank after `seq` has been signalled. Returns the
+/// new sequence number, to pass back in for the following frame.
+pub fn wait_for_vblank(seq: u64) -> u64 {
+    loop {
+        let now = VBLANK.seq();
+        if now > seq {
+            return now;
+        }
+        crate::sched::yield_cpu();
+    }
+}
+
+/// Set the vblank rate in mHz (e.g. 60000 for 60 Hz).
+pub(crate) fn set_vblank_refresh_mhz(mhz: u32) {
+    if mhz > 0 {
+        VBLANK.set_interval_ns(1_000_000_000_000u64 / mhz as u64);
+    }
+}
+
 // ===========================================================================
 // Tests
 // ===========================================================================
@@ -521,6 +633,43 @@ mod tests {
         assert!(!state.is_enabled());
     }
 
+    #[test]
+    fn test_vblank_clock_advance() {
+        let clock = VblankClock::new(1_000);
+        // The first call only arms the deadline
+        assert!(!clock.advance(5_000));
+        assert_eq!(clock.seq(), 0);
+        assert!(!clock.advance(5_999));
+        assert!(clock.advance(6_000));
+        assert_eq!(clock.seq(), 1);
+        // Already signalled for this interval
+        assert!(!clock.advance(6_500));
+        assert_eq!(clock.seq(), 1);
+    }
+
+    #[test]
+    fn test_vblank_clock_folds_missed() {
+        let clock = VblankClock::new(1_000);
+        clock.advance(0);
+        // Five intervals late: one vblank, next deadline stays on the grid
+        assert!(clock.advance(5_500));
+        assert_eq!(clock.seq(), 1);
+        assert!(!clock.advance(5_999));
+        assert!(clock.advance(6_000));
+        assert_eq!(clock.seq(), 2);
+    }
+
+    #[test]
+    fn test_vblank_clock_interval() {
+        let clock = VblankClock::new(1_000);
+        clock.set_interval_ns(0);
+        assert_eq!(clock.interval_ns(), 1_000);
+        clock.set_interval_ns(2_000);
+        clock.advance(0);
+        assert!(!clock.advance(1_999));
+        assert!(clock.advance(2_000));
+    }
+
     #[test]
     fn test_frame_stats_circular_buffer() {
         let mut stats = FrameStats::new();
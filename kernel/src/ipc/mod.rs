@@ -14,6 +14,7 @@ pub mod error;
 pub mod fast_path;
 pub mod message;
 pub mod message_passing;
+pub mod namespace;
 pub mod perf;
 pub mod posix_shm;
 pub mod rate_limit;
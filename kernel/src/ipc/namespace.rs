@@ -0,0 +1,550 @@
+//! Per-service IPC namespaces
+//!
+//! Service names (e.g. `"blockdev"`, `"netstack"`) are resolved relative to
+//! the caller's namespace rather than a single global table. Every process
+//! belongs to exactly one namespace; processes that were never assigned one
+//! live in the root namespace.
+//!
+//! A sandboxed application is placed in a fresh namespace that starts out
+//! empty. Its supervisor explicitly exports the services the sandbox may
+//! use, so a lookup for anything else (such as the block-device driver
+//! endpoint) fails exactly as if the name did not exist -- the sandbox
+//! cannot even discover that the service is running.
+//!
+//! The process that creates a namespace supervises it: only the supervisor
+//! may move processes into it or export services to it, and it can only
+//! export services bound in its own namespace. The capability checks on top
+//! of that (a Process capability for the process being moved, an endpoint
+//! capability for the service being exported) are made by the syscalls.
+//!
+//! Name bindings only map a name to an endpoint ID. Talking to the endpoint
+//! still requires a capability; lookups mint a send-only capability into
+//! the caller's capability space (see `sys_ipc_lookup_endpoint`).
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use spin::Mutex;
+
+use super::{
+    capability::{EndpointId, ProcessId},
+    error::{IpcError, Result},
+};
+
+/// Namespace identifier.
+pub type NamespaceId = u64;
+
+/// The root namespace. Processes without an explicit assignment live here.
+pub const ROOT_NAMESPACE: NamespaceId = 0;
+
+/// Maximum length of a service name in bytes.
+pub const SERVICE_NAME_MAX: usize = 64;
+
+/// Maximum number of names bound in a single namespace.
+pub const MAX_NAMES_PER_NAMESPACE: usize = 256;
+
+/// A single service namespace.
+#[derive(Debug, Default)]
+struct Namespace {
+    /// Namespace this one was created from (for diagnostics only; lookups
+    /// never fall through to the parent).
+    parent: Option<NamespaceId>,
+    /// Process that created the namespace and controls its membership and
+    /// exports. Cleared when that process exits.
+    supervisor: Option<ProcessId>,
+    /// Service name -> endpoint bindings visible in this namespace.
+    names: BTreeMap<String, EndpointId>,
+}
+
+/// Table of all namespaces and process membership.
+pub struct NamespaceTable {
+    namespaces: BTreeMap<NamespaceId, Namespace>,
+    process_ns: BTreeMap<ProcessId, NamespaceId>,
+    next_id: NamespaceId,
+}
+
+impl Default for NamespaceTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NamespaceTable {
+    /// Create an empty table. The root namespace is created lazily on the
+    /// first bind so this can be used in a `static`.
+    pub const fn new() -> Self {
+        Self {
+            namespaces: BTreeMap::new(),
+            process_ns: BTreeMap::new(),
+            next_id: ROOT_NAMESPACE + 1,
+        }
+    }
+
+    /// Create a new, empty namespace derived from `parent` and supervised
+    /// by `supervisor`.
+    pub fn create(
+        &mut self,
+        parent: NamespaceId,
+        supervisor: Option<ProcessId>,
+    ) -> Result<NamespaceId> {
+        if parent != ROOT_NAMESPACE && !self.namespaces.contains_key(&parent) {
+            return Err(IpcError::InvalidMessage);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.namespaces.insert(
+            id,
+            Namespace {
+                parent: Some(parent),
+                supervisor,
+                names: BTreeMap::new(),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Destroy a namespace. Processes still assigned to it fall back to the
+    /// root namespace.
+    pub fn destroy(&mut self, ns: NamespaceId) -> Result<()> {
+        if ns == ROOT_NAMESPACE {
+            return Err(IpcError::PermissionDenied);
+        }
+        self.namespaces
+            .remove(&ns)
+            .ok_or(IpcError::InvalidMessage)?;
+        self.process_ns.retain(|_, assigned| *assigned != ns);
+        Ok(())
+    }
+
+    /// Namespace the given process resolves names in.
+    pub fn namespace_of(&self, pid: ProcessId) -> NamespaceId {
+        self.process_ns.get(&pid).copied().unwrap_or(ROOT_NAMESPACE)
+    }
+
+    /// Move a process into `ns`.
+    pub fn assign(&mut self, pid: ProcessId, ns: NamespaceId) -> Result<()> {
+        if ns == ROOT_NAMESPACE {
+            self.process_ns.remove(&pid);
+            return Ok(());
+        }
+        if !self.namespaces.contains_key(&ns) {
+            return Err(IpcError::InvalidMessage);
+        }
+        self.process_ns.insert(pid, ns);
+        Ok(())
+    }
+
+    /// Bind `name` to `endpoint` in namespace `ns`.
+    pub fn bind(&mut self, ns: NamespaceId, name: &str, endpoint: EndpointId) -> Result<()> {
+        if name.is_empty() || name.len() > SERVICE_NAME_MAX {
+            return Err(IpcError::InvalidMessage);
+        }
+        let namespace = if ns == ROOT_NAMESPACE {
+            self.namespaces.entry(ROOT_NAMESPACE).or_default()
+        } else {
+            self.namespaces
+                .get_mut(&ns)
+                .ok_or(IpcError::InvalidMessage)?
+        };
+        if namespace.names.contains_key(name) {
+            return Err(IpcError::EndpointBusy);
+        }
+        if namespace.names.len() >= MAX_NAMES_PER_NAMESPACE {
+            return Err(IpcError::OutOfMemory);
+        }
+        namespace.names.insert(String::from(name), endpoint);
+        Ok(())
+    }
+
+    /// Remove a name binding from namespace `ns`.
+    pub fn unbind(&mut self, ns: NamespaceId, name: &str) -> Result<EndpointId> {
+        self.namespaces
+            .get_mut(&ns)
+            .and_then(|namespace| namespace.names.remove(name))
+            .ok_or(IpcError::EndpointNotFound)
+    }
+
+    /// Resolve `name` in namespace `ns`.
+    ///
+    /// Names bound in other namespaces are never visible, and the error is
+    /// the same whether the name is bound elsewhere or not at all.
+    pub fn lookup(&self, ns: NamespaceId, name: &str) -> Result<EndpointId> {
+        self.namespaces
+            .get(&ns)
+            .and_then(|namespace| namespace.names.get(name))
+            .copied()
+            .ok_or(IpcError::EndpointNotFound)
+    }
+
+    /// Make the endpoint bound to `name` in `from` visible in `to` as well.
+    pub fn export(&mut self, from: NamespaceId, name: &str, to: NamespaceId) -> Result<()> {
+        let endpoint = self.lookup(from, name)?;
+        self.bind(to, name, endpoint)
+    }
+
+    /// Whether `supervisor` may place processes in `ns` and export services
+    /// to it: `ns` is its own namespace or one it created.
+    fn supervises(&self, supervisor: ProcessId, ns: NamespaceId) -> bool {
+        ns == self.namespace_of(supervisor)
+            || self
+                .namespaces
+                .get(&ns)
+                .is_some_and(|namespace| namespace.supervisor == Some(supervisor))
+    }
+
+    /// Create an empty namespace supervised by `supervisor`, derived from
+    /// its own namespace.
+    pub fn create_supervised(&mut self, supervisor: ProcessId) -> Result<NamespaceId> {
+        let parent = self.namespace_of(supervisor);
+        self.create(parent, Some(supervisor))
+    }
+
+    /// Move `pid` into `ns` on behalf of `supervisor`. Both the namespace
+    /// `pid` is leaving and `ns` must be supervised by `supervisor`, so a
+    /// supervisor cannot take processes out of sandboxes it does not run.
+    pub fn assign_supervised(
+        &mut self,
+        supervisor: ProcessId,
+        pid: ProcessId,
+        ns: NamespaceId,
+    ) -> Result<()> {
+        if !self.supervises(supervisor, self.namespace_of(pid)) || !self.supervises(supervisor, ns)
+        {
+            return Err(IpcError::PermissionDenied);
+        }
+        self.assign(pid, ns)
+    }
+
+    /// Export `name` from `supervisor`'s namespace into `ns`. The name must
+    /// be bound to `endpoint`, the endpoint the supervisor proved it holds
+    /// a capability for.
+    pub fn export_supervised(
+        &mut self,
+        supervisor: ProcessId,
+        name: &str,
+        endpoint: EndpointId,
+        ns: NamespaceId,
+    ) -> Result<()> {
+        let from = self.namespace_of(supervisor);
+        if ns == from || !self.supervises(supervisor, ns) {
+            return Err(IpcError::PermissionDenied);
+        }
+        if self.lookup(from, name)? != endpoint {
+            return Err(IpcError::PermissionDenied);
+        }
+        self.export(from, name, ns)
+    }
+
+    /// Drop every binding that refers to `endpoint` (endpoint destroyed).
+    pub fn remove_endpoint(&mut self, endpoint: EndpointId) -> usize {
+        let mut removed = 0;
+        for namespace in self.namespaces.values_mut() {
+            let before = namespace.names.len();
+            namespace.names.retain(|_, ep| *ep != endpoint);
+            removed += before - namespace.names.len();
+        }
+        removed
+    }
+
+    /// Forget a process's namespace assignment and supervision (process
+    /// exit). Namespaces it supervised keep their members and bindings but
+    /// can no longer be changed.
+    pub fn remove_process(&mut self, pid: ProcessId) {
+        self.process_ns.remove(&pid);
+        for namespace in self.namespaces.values_mut() {
+            if namespace.supervisor == Some(pid) {
+                namespace.supervisor = None;
+            }
+        }
+    }
+
+    /// List the names visible in namespace `ns`.
+    pub fn names(&self, ns: NamespaceId) -> Vec<(String, EndpointId)> {
+        self.namespaces
+            .get(&ns)
+            .map(|namespace| {
+                namespace
+                    .names
+                    .iter()
+                    .map(|(name, ep)| (name.clone(), *ep))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Parent a namespace was created from, if any.
+    pub fn parent_of(&self, ns: NamespaceId) -> Option<NamespaceId> {
+        self.namespaces
+            .get(&ns)
+            .and_then(|namespace| namespace.parent)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global namespace table
+// ---------------------------------------------------------------------------
+
+static NAMESPACES: Mutex<NamespaceTable> = Mutex::new(NamespaceTable::new());
+
+/// Create a new empty namespace derived from `parent`, with no supervisor.
+pub fn create_namespace(parent: NamespaceId) -> Result<NamespaceId> {
+    NAMESPACES.lock().create(parent, None)
+}
+
+/// Create an empty namespace supervised by `supervisor`.
+pub fn create_supervised(supervisor: ProcessId) -> Result<NamespaceId> {
+    NAMESPACES.lock().create_supervised(supervisor)
+}
+
+/// Move `pid` into `ns` on behalf of its supervisor.
+pub fn assign_supervised(supervisor: ProcessId, pid: ProcessId, ns: NamespaceId) -> Result<()> {
+    NAMESPACES.lock().assign_supervised(supervisor, pid, ns)
+}
+
+/// Export a service from the supervisor's namespace into `ns`.
+pub fn export_supervised(
+    supervisor: ProcessId,
+    name: &str,
+    endpoint: EndpointId,
+    ns: NamespaceId,
+) -> Result<()> {
+    NAMESPACES
+        .lock()
+        .export_supervised(supervisor, name, endpoint, ns)
+}
+
+/// Destroy a namespace; its members fall back to the root namespace.
+pub fn destroy_namespace(ns: NamespaceId) -> Result<()> {
+    NAMESPACES.lock().destroy(ns)
+}
+
+/// Namespace the given process resolves service names in.
+pub fn namespace_of(pid: ProcessId) -> NamespaceId {
+    NAMESPACES.lock().namespace_of(pid)
+}
+
+/// Move a process into a namespace.
+pub fn assign_process(pid: ProcessId, ns: NamespaceId) -> Result<()> {
+    NAMESPACES.lock().assign(pid, ns)
+}
+
+/// Give `child` the same namespace as `parent` (fork/spawn).
+pub fn inherit_namespace(parent: ProcessId, child: ProcessId) {
+    let mut table = NAMESPACES.lock();
+    let ns = table.namespace_of(parent);
+    let _ = table.assign(child, ns);
+}
+
+/// Bind a service name in the caller's namespace.
+pub fn bind_name(pid: ProcessId, name: &str, endpoint: EndpointId) -> Result<()> {
+    let mut table = NAMESPACES.lock();
+    let ns = table.namespace_of(pid);
+    table.bind(ns, name, endpoint)
+}
+
+/// Resolve a service name in the caller's namespace.
+pub fn lookup_name(pid: ProcessId, name: &str) -> Result<EndpointId> {
+    let table = NAMESPACES.lock();
+    table.lookup(table.namespace_of(pid), name)
+}
+
+/// Export a service bound in `from` into namespace `to`.
+pub fn export_name(from: NamespaceId, name: &str, to: NamespaceId) -> Result<()> {
+    NAMESPACES.lock().export(from, name, to)
+}
+
+/// Drop all name bindings pointing at a destroyed endpoint.
+pub fn remove_endpoint_names(endpoint: EndpointId) -> usize {
+    NAMESPACES.lock().remove_endpoint(endpoint)
+}
+
+/// Forget a process's namespace assignment.
+pub fn remove_process(pid: ProcessId) {
+    NAMESPACES.lock().remove_process(pid)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_namespace_default() {
+        let table = NamespaceTable::new();
+        assert_eq!(table.namespace_of(ProcessId(42)), ROOT_NAMESPACE);
+    }
+
+    #[test]
+    fn test_bind_and_lookup_in_root() {
+        let mut table = NamespaceTable::new();
+        table.bind(ROOT_NAMESPACE, "blockdev", 7).unwrap();
+        assert_eq!(table.lookup(ROOT_NAMESPACE, "blockdev"), Ok(7));
+        assert_eq!(
+            table.bind(ROOT_NAMESPACE, "blockdev", 8),
+            Err(IpcError::EndpointBusy)
+        );
+    }
+
+    #[test]
+    fn test_sandbox_cannot_discover_unexported_names() {
+        let mut table = NamespaceTable::new();
+        table.bind(ROOT_NAMESPACE, "blockdev", 7).unwrap();
+        table.bind(ROOT_NAMESPACE, "compositor", 9).unwrap();
+
+        let sandbox = table.create(ROOT_NAMESPACE, None).unwrap();
+        table.assign(ProcessId(100), sandbox).unwrap();
+        table.export(ROOT_NAMESPACE, "compositor", sandbox).unwrap();
+
+        let ns = table.namespace_of(ProcessId(100));
+        assert_eq!(ns, sandbox);
+        assert_eq!(table.lookup(ns, "compositor"), Ok(9));
+        assert_eq!(
+            table.lookup(ns, "blockdev"),
+            Err(IpcError::EndpointNotFound)
+        );
+        assert_eq!(
+            table.lookup(ns, "no-such-service"),
+            Err(IpcError::EndpointNotFound)
+        );
+    }
+
+    #[test]
+    fn test_remove_endpoint_drops_all_bindings() {
+        let mut table = NamespaceTable::new();
+        let sandbox = table.create(ROOT_NAMESPACE, None).unwrap();
+        table.bind(ROOT_NAMESPACE, "net", 3).unwrap();
+        table.export(ROOT_NAMESPACE, "net", sandbox).unwrap();
+
+        assert_eq!(table.remove_endpoint(3), 2);
+        assert!(table.lookup(ROOT_NAMESPACE, "net").is_err());
+        assert!(table.lookup(sandbox, "net").is_err());
+    }
+
+    #[test]
+    fn test_destroy_namespace_returns_members_to_root() {
+        let mut table = NamespaceTable::new();
+        let sandbox = table.create(ROOT_NAMESPACE, None).unwrap();
+        table.assign(ProcessId(5), sandbox).unwrap();
+        table.destroy(sandbox).unwrap();
+        assert_eq!(table.namespace_of(ProcessId(5)), ROOT_NAMESPACE);
+        assert_eq!(
+            table.destroy(ROOT_NAMESPACE),
+            Err(IpcError::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn test_supervisor_controls_its_namespaces() {
+        let mut table = NamespaceTable::new();
+        let supervisor = ProcessId(10);
+        let app = ProcessId(11);
+        table.bind(ROOT_NAMESPACE, "compositor", 9).unwrap();
+        table.bind(ROOT_NAMESPACE, "blockdev", 7).unwrap();
+
+        let sandbox = table.create_supervised(supervisor).unwrap();
+        assert_eq!(table.parent_of(sandbox), Some(ROOT_NAMESPACE));
+        table.assign_supervised(supervisor, app, sandbox).unwrap();
+        table
+            .export_supervised(supervisor, "compositor", 9, sandbox)
+            .unwrap();
+
+        assert_eq!(table.namespace_of(app), sandbox);
+        assert_eq!(table.lookup(sandbox, "compositor"), Ok(9));
+        assert_eq!(
+            table.lookup(sandbox, "blockdev"),
+            Err(IpcError::EndpointNotFound)
+        );
+
+        // Releasing the process back into the supervisor's namespace
+        table
+            .assign_supervised(supervisor, app, ROOT_NAMESPACE)
+            .unwrap();
+        assert_eq!(table.namespace_of(app), ROOT_NAMESPACE);
+    }
+
+    #[test]
+    fn test_other_processes_cannot_supervise() {
+        let mut table = NamespaceTable::new();
+        let supervisor = ProcessId(10);
+        let other = ProcessId(20);
+        table.bind(ROOT_NAMESPACE, "net", 3).unwrap();
+        let sandbox = table.create_supervised(supervisor).unwrap();
+        table
+            .assign_supervised(supervisor, ProcessId(11), sandbox)
+            .unwrap();
+
+        assert_eq!(
+            table.assign_supervised(other, ProcessId(12), sandbox),
+            Err(IpcError::PermissionDenied)
+        );
+        assert_eq!(
+            table.export_supervised(other, "net", 3, sandbox),
+            Err(IpcError::PermissionDenied)
+        );
+        // Nor take a process out of a sandbox it does not run
+        assert_eq!(
+            table.assign_supervised(other, ProcessId(11), ROOT_NAMESPACE),
+            Err(IpcError::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn test_sandboxed_process_cannot_escape() {
+        let mut table = NamespaceTable::new();
+        let supervisor = ProcessId(10);
+        let app = ProcessId(11);
+        let sandbox = table.create_supervised(supervisor).unwrap();
+        table.assign_supervised(supervisor, app, sandbox).unwrap();
+
+        // A namespace the sandboxed process creates nests inside its own
+        let nested = table.create_supervised(app).unwrap();
+        assert_eq!(table.parent_of(nested), Some(sandbox));
+        assert_eq!(
+            table.assign_supervised(app, app, ROOT_NAMESPACE),
+            Err(IpcError::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn test_export_requires_matching_endpoint() {
+        let mut table = NamespaceTable::new();
+        let supervisor = ProcessId(10);
+        table.bind(ROOT_NAMESPACE, "blockdev", 7).unwrap();
+        let sandbox = table.create_supervised(supervisor).unwrap();
+
+        // A capability for some other endpoint does not cover `blockdev`
+        assert_eq!(
+            table.export_supervised(supervisor, "blockdev", 8, sandbox),
+            Err(IpcError::PermissionDenied)
+        );
+        assert_eq!(
+            table.export_supervised(supervisor, "missing", 7, sandbox),
+            Err(IpcError::EndpointNotFound)
+        );
+        assert!(table.lookup(sandbox, "blockdev").is_err());
+    }
+
+    #[test]
+    fn test_supervisor_exit_freezes_namespace() {
+        let mut table = NamespaceTable::new();
+        let supervisor = ProcessId(10);
+        let sandbox = table.create_supervised(supervisor).unwrap();
+        table
+            .assign_supervised(supervisor, ProcessId(11), sandbox)
+            .unwrap();
+
+        table.remove_process(supervisor);
+        assert_eq!(table.namespace_of(ProcessId(11)), sandbox);
+        // A new process reusing the PID does not inherit supervision
+        assert_eq!(
+            table.assign_supervised(supervisor, ProcessId(12), sandbox),
+            Err(IpcError::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_names() {
+        let mut table = NamespaceTable::new();
+        assert!(table.bind(ROOT_NAMESPACE, "", 1).is_err());
+        let long = "x".repeat(SERVICE_NAME_MAX + 1);
+        assert!(table.bind(ROOT_NAMESPACE, &long, 1).is_err());
+    }
+}
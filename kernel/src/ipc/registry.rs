@@ -51,6 +51,10 @@ pub struct IpcRegistry {
     /// Process to endpoints mapping
     #[cfg(feature = "alloc")]
     process_endpoints: BTreeMap<ProcessId, BTreeMap<EndpointId, IpcCapability>>,
+    /// Capability ID to endpoint binding (shared by delegated copies, which
+    /// keep the same capability ID)
+    #[cfg(feature = "alloc")]
+    cap_bindings: BTreeMap<u64, EndpointId>,
     /// Next endpoint ID
     next_endpoint_id: AtomicU64,
     /// Statistics
@@ -77,6 +81,8 @@ impl IpcRegistry {
             channels: BTreeMap::new(),
             #[cfg(feature = "alloc")]
             process_endpoints: BTreeMap::new(),
+            #[cfg(feature = "alloc")]
+            cap_bindings: BTreeMap::new(),
             next_endpoint_id: AtomicU64::new(1),
             stats: RegistryStats {
                 endpoints_created: AtomicU64::new(0),
@@ -92,6 +98,9 @@ impl IpcRegistry {
     /// Create a new endpoint
     #[cfg(feature = "alloc")]
     pub fn create_endpoint(&mut self, owner: ProcessId) -> Result<(EndpointId, IpcCapability)> {
+        if self.endpoint_count() >= crate::limits::max_ipc_endpoints() {
+            return Err(IpcError::OutOfMemory);
+        }
         let endpoint_id = self.next_endpoint_id.fetch_add(1, Ordering::Relaxed);
         let endpoint = Endpoint::new(owner);
 
@@ -124,6 +133,9 @@ impl IpcRegistry {
         owner: ProcessId,
         capacity: usize,
     ) -> Result<(EndpointId, EndpointId, IpcCapability, IpcCapability)> {
+        if self.endpoint_count() + 2 > crate::limits::max_ipc_endpoints() {
+            return Err(IpcError::OutOfMemory);
+        }
         let channel = Channel::new(owner, capacity);
         let send_id = channel.send_id();
         let recv_id = channel.receive_id();
@@ -235,6 +247,8 @@ impl IpcRegistry {
 
         // Remove from registry
         self.endpoints.remove(&id);
+        self.unbind_endpoint_capabilities(id);
+        super::namespace::remove_endpoint_names(id);
 
         // Remove from process's endpoint list
         if let Some(process_eps) = self.process_endpoints.get_mut(&owner) {
@@ -253,6 +267,49 @@ impl IpcRegistry {
         Err(IpcError::EndpointNotFound)
     }
 
+    /// Bind a kernel capability ID to the endpoint it grants access to
+    #[cfg(feature = "alloc")]
+    pub fn bind_capability(&mut self, cap_id: u64, endpoint: EndpointId) -> Result<()> {
+        if !self.endpoints.contains_key(&endpoint) && !self.channels.contains_key(&endpoint) {
+            return Err(IpcError::EndpointNotFound);
+        }
+        self.cap_bindings.insert(cap_id, endpoint);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    pub fn bind_capability(&mut self, _cap_id: u64, _endpoint: EndpointId) -> Result<()> {
+        Err(IpcError::OutOfMemory)
+    }
+
+    /// Resolve a capability ID to the endpoint it was bound to
+    #[cfg(feature = "alloc")]
+    pub fn endpoint_for_capability(&self, cap_id: u64) -> Option<EndpointId> {
+        self.cap_bindings.get(&cap_id).copied()
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    pub fn endpoint_for_capability(&self, _cap_id: u64) -> Option<EndpointId> {
+        None
+    }
+
+    /// Drop every capability binding that points at `endpoint`
+    #[cfg(feature = "alloc")]
+    fn unbind_endpoint_capabilities(&mut self, endpoint: EndpointId) {
+        self.cap_bindings.retain(|_, ep| *ep != endpoint);
+    }
+
+    /// Number of live endpoints, counting both ends of every channel
+    #[cfg(feature = "alloc")]
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len() + 2 * self.channels.len()
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    pub fn endpoint_count(&self) -> usize {
+        0
+    }
+
     /// Get registry statistics
     pub fn get_stats(&self) -> RegistryStatsSummary {
         RegistryStatsSummary {
@@ -313,6 +370,11 @@ pub fn create_endpoint(owner: ProcessId) -> Result<(EndpointId, IpcCapability)>
     with_registry_mut(|registry| registry.create_endpoint(owner))
 }
 
+/// Number of live endpoints in the global registry
+pub fn endpoint_count() -> usize {
+    with_registry(|registry| Ok(registry.endpoint_count())).unwrap_or(0)
+}
+
 /// Create a channel through the global registry
 pub fn create_channel(
     owner: ProcessId,
@@ -388,6 +450,11 @@ pub fn remove_process_endpoints(owner: ProcessId) -> Result<usize> {
                         .channels_destroyed
                         .fetch_add(1, Ordering::Relaxed);
                 }
+
+                // Capabilities and service names for a dead endpoint must not
+                // resolve to a recycled one later
+                registry.unbind_endpoint_capabilities(*endpoint_id);
+                super::namespace::remove_endpoint_names(*endpoint_id);
             }
 
             // Remove the process's endpoint mapping entirely
@@ -443,6 +510,20 @@ pub fn try_receive_from_endpoint(endpoint_id: u64) -> Option<Message> {
     .flatten()
 }
 
+/// Bind a kernel capability ID to an endpoint in the global registry
+pub fn bind_capability(cap_id: u64, endpoint: EndpointId) -> Result<()> {
+    with_registry_mut(|registry| registry.bind_capability(cap_id, endpoint))
+}
+
+/// Resolve a kernel capability ID to its bound endpoint
+pub fn endpoint_for_capability(cap_id: u64) -> Result<EndpointId> {
+    with_registry(|registry| {
+        registry
+            .endpoint_for_capability(cap_id)
+            .ok_or(IpcError::InvalidCapability)
+    })
+}
+
 /// Validate a capability
 pub fn validate_capability(process: ProcessId, capability: &IpcCapability) -> Result<()> {
     with_registry(|registry| registry.validate_capability(process, capability))
@@ -483,4 +564,20 @@ mod tests {
         assert!(recv_cap.has_permission(super::super::capability::Permission::Receive));
         assert!(!recv_cap.has_permission(super::super::capability::Permission::Send));
     }
+
+    #[test]
+    fn test_capability_binding() {
+        init();
+        let (id, _cap) = create_endpoint(ProcessId(1)).unwrap();
+        bind_capability(0xABCD, id).unwrap();
+        assert_eq!(endpoint_for_capability(0xABCD), Ok(id));
+        assert_eq!(
+            endpoint_for_capability(0xABCE),
+            Err(IpcError::InvalidCapability)
+        );
+        assert_eq!(
+            bind_capability(0xABCF, u64::MAX),
+            Err(IpcError::EndpointNotFound)
+        );
+    }
 }
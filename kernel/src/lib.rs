@@ -28,13 +28,23 @@ mod simple_alloc_unsafe;
 use simple_alloc_unsafe::{LockedUnsafeBumpAllocator, UnsafeBumpAllocator};
 
 #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-#[global_allocator]
 static ALLOCATOR: LockedHeap = LockedHeap::empty();
 
 #[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
-#[global_allocator]
 pub static ALLOCATOR: UnsafeBumpAllocator = UnsafeBumpAllocator::new();
 
+// The kernel heap is reached through the profiler's wrapper, which only
+// forwards while `memprof` is off.
+#[cfg(all(target_arch = "x86_64", target_os = "none"))]
+#[global_allocator]
+static PROFILED_ALLOCATOR: mm::heap_profile::Profiled<LockedHeap> =
+    mm::heap_profile::Profiled(&ALLOCATOR);
+
+#[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
+#[global_allocator]
+static PROFILED_ALLOCATOR: mm::heap_profile::Profiled<UnsafeBumpAllocator> =
+    mm::heap_profile::Profiled(&ALLOCATOR);
+
 #[cfg(any(target_arch = "riscv64", target_arch = "aarch64"))]
 pub static LOCKED_ALLOCATOR: LockedUnsafeBumpAllocator = LockedUnsafeBumpAllocator::empty();
 
@@ -65,23 +75,32 @@ mod intrinsics;
 
 pub mod arch;
 pub mod audio;
+#[cfg(feature = "alloc")]
+pub mod bootslot;
 pub mod bootstrap;
 mod cap;
+pub mod crash;
 pub mod crypto;
+#[cfg(feature = "desktop")]
 pub mod desktop;
 pub mod drivers;
 pub mod elf;
 pub mod error;
 pub mod fs;
+// Always built for the framebuffer console; the compositor and GPU stack
+// inside it are behind the `graphics` feature.
 pub mod graphics;
 pub mod ipc;
 pub mod irq;
+pub mod limits;
 pub mod log_service;
 pub mod media;
 pub mod mm;
+#[cfg(feature = "net")]
 pub mod net;
 pub mod perf;
 pub mod phase2_validation;
+#[cfg(feature = "pkg")]
 pub mod pkg;
 pub mod power;
 pub mod process;
@@ -115,6 +134,9 @@ pub mod devtools;
 #[allow(dead_code)]
 #[cfg(feature = "alloc")]
 pub mod verification;
+#[allow(dead_code)]
+#[cfg(feature = "alloc")]
+pub mod wasm;
 
 #[cfg(test)]
 mod test_config;
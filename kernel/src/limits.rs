@@ -0,0 +1,292 @@
+//! Kernel resource limits
+//!
+//! Maximum processes, threads per process, open files per process and IPC
+//! endpoints are configured once at boot instead of being fixed table sizes:
+//!
+//! 1. Defaults are scaled from the detected RAM (the historical fixed values
+//!    correspond to a 256 MiB machine).
+//! 2. The kernel command line may override them with `limits.<name>=<n>`, e.g.
+//!    `limits.max_processes=4096`.
+//! 3. Root may adjust them at runtime through `sysctl kernel.limits.<name>`.
+//!
+//! All values are clamped to `1..=ceiling`. Current usage and the configured
+//! maxima are reported in `/proc/limits` (system-wide) and
+//! `/proc/<pid>/limits` (per process), so exhaustion is visible before it
+//! causes failures.
+
+use alloc::{format, string::String};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::KernelError;
+
+/// Configurable resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Processes in the system, including zombies awaiting reaping.
+    Processes,
+    /// Threads in a single process.
+    ThreadsPerProcess,
+    /// Open file descriptors in a single process.
+    OpenFilesPerProcess,
+    /// IPC endpoints in the system.
+    IpcEndpoints,
+}
+
+impl Limit {
+    /// All configurable resources.
+    pub const ALL: [Limit; 4] = [
+        Limit::Processes,
+        Limit::ThreadsPerProcess,
+        Limit::OpenFilesPerProcess,
+        Limit::IpcEndpoints,
+    ];
+
+    /// Parameter name used on the command line (`limits.<name>`) and in
+    /// sysctl (`kernel.limits.<name>`).
+    pub const fn name(self) -> &'static str {
+        match self {
+            Limit::Processes => "max_processes",
+            Limit::ThreadsPerProcess => "max_threads",
+            Limit::OpenFilesPerProcess => "max_open_files",
+            Limit::IpcEndpoints => "max_ipc_endpoints",
+        }
+    }
+
+    /// Look up a resource by parameter name.
+    pub fn from_name(name: &str) -> Option<Limit> {
+        Self::ALL.into_iter().find(|l| l.name() == name)
+    }
+
+    /// `(per MiB of RAM, minimum, ceiling)` used to derive defaults.
+    const fn scaling(self) -> (usize, usize, usize) {
+        match self {
+            Limit::Processes => (4, 64, 32768),
+            Limit::ThreadsPerProcess => (1, 64, 4096),
+            Limit::OpenFilesPerProcess => (4, 256, 65536),
+            Limit::IpcEndpoints => (16, 256, 65536),
+        }
+    }
+
+    /// Largest value that may be configured.
+    pub const fn ceiling(self) -> usize {
+        self.scaling().2
+    }
+
+    /// Default for a machine with `total_memory` bytes of RAM.
+    pub fn default_for_memory(self, total_memory: usize) -> usize {
+        let (per_mib, min, ceiling) = self.scaling();
+        let mib = total_memory / (1024 * 1024);
+        mib.saturating_mul(per_mib).clamp(min, ceiling)
+    }
+
+    /// `value` clamped to `1..=ceiling`, as [`set`] applies it.
+    pub fn bounded(self, value: usize) -> usize {
+        value.clamp(1, self.ceiling())
+    }
+
+    /// Value [`configure`] applies: the `limits.<name>` override on
+    /// `cmdline`, or the default for `total_memory` bytes of RAM, bounded.
+    fn configured(self, total_memory: usize, cmdline: &str) -> usize {
+        let key = format!("limits.{}", self.name());
+        let value = crate::utils::cmdline::find_param(cmdline, &key)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| self.default_for_memory(total_memory));
+        self.bounded(value)
+    }
+
+    fn slot(self) -> &'static AtomicUsize {
+        match self {
+            Limit::Processes => &MAX_PROCESSES,
+            Limit::ThreadsPerProcess => &MAX_THREADS,
+            Limit::OpenFilesPerProcess => &MAX_OPEN_FILES,
+            Limit::IpcEndpoints => &MAX_IPC_ENDPOINTS,
+        }
+    }
+}
+
+/// Defaults before [`init`] runs (256 MiB machine).
+const BASELINE_MEMORY: usize = 256 * 1024 * 1024;
+
+static MAX_PROCESSES: AtomicUsize = AtomicUsize::new(1024);
+static MAX_THREADS: AtomicUsize = AtomicUsize::new(256);
+static MAX_OPEN_FILES: AtomicUsize = AtomicUsize::new(1024);
+static MAX_IPC_ENDPOINTS: AtomicUsize = AtomicUsize::new(4096);
+
+/// Current maximum for `limit`.
+pub fn get(limit: Limit) -> usize {
+    limit.slot().load(Ordering::Relaxed)
+}
+
+/// Set the maximum for `limit`, clamped to `1..=ceiling`. Returns the value
+/// actually applied.
+///
+/// Lowering a limit below current usage does not reclaim anything; it only
+/// makes further allocations fail until usage drops.
+pub fn set(limit: Limit, value: usize) -> usize {
+    let value = limit.bounded(value);
+    limit.slot().store(value, Ordering::Relaxed);
+    value
+}
+
+/// Parse and apply a `name=value` pair (sysctl helper).
+pub fn set_by_name(name: &str, value: &str) -> Result<usize, KernelError> {
+    let limit = Limit::from_name(name).ok_or(KernelError::InvalidArgument {
+        name: "limit",
+        value: "unknown limit name",
+    })?;
+    let value = value
+        .trim()
+        .parse()
+        .map_err(|_| KernelError::InvalidArgument {
+            name: "value",
+            value: "not a number",
+        })?;
+    Ok(set(limit, value))
+}
+
+/// Compute limits from `total_memory` and apply `limits.*` overrides from
+/// `cmdline`.
+pub fn configure(total_memory: usize, cmdline: &str) {
+    for limit in Limit::ALL {
+        set(limit, limit.configured(total_memory, cmdline));
+    }
+}
+
+/// Configure limits from detected RAM and the boot command line. Called
+/// once memory management is up, before the first process is created.
+pub fn init() {
+    let stats = crate::mm::get_memory_stats();
+    let total = stats.total_frames * crate::mm::FRAME_SIZE;
+    let total = if total == 0 { BASELINE_MEMORY } else { total };
+    configure(total, &crate::utils::cmdline::get());
+    crate::println!(
+        "[LIMITS] processes={} threads/proc={} files/proc={} ipc_endpoints={}",
+        get(Limit::Processes),
+        get(Limit::ThreadsPerProcess),
+        get(Limit::OpenFilesPerProcess),
+        get(Limit::IpcEndpoints)
+    );
+}
+
+/// Maximum number of processes.
+pub fn max_processes() -> usize {
+    get(Limit::Processes)
+}
+
+/// Maximum threads per process.
+pub fn max_threads_per_process() -> usize {
+    get(Limit::ThreadsPerProcess)
+}
+
+/// Maximum open file descriptors per process.
+pub fn max_open_files() -> usize {
+    get(Limit::OpenFilesPerProcess)
+}
+
+/// Maximum number of IPC endpoints.
+pub fn max_ipc_endpoints() -> usize {
+    get(Limit::IpcEndpoints)
+}
+
+// ---------------------------------------------------------------------------
+// Usage reporting
+// ---------------------------------------------------------------------------
+
+/// Format `/proc/limits`: system-wide usage against the configured maxima.
+///
+/// For per-process resources the "current" column is the largest usage of
+/// any single process.
+pub fn format_system_limits() -> String {
+    let mut threads_peak = 0;
+    let mut files_peak = 0;
+    crate::process::table::PROCESS_TABLE.for_each(|p| {
+        threads_peak = threads_peak.max(p.thread_count());
+        files_peak = files_peak.max(p.file_table.lock().count_open());
+    });
+    let endpoints = crate::ipc::registry::endpoint_count();
+
+    let mut out = String::from("Resource              Current    Max\n");
+    for (limit, current) in [
+        (Limit::Processes, crate::process::table::process_count()),
+        (Limit::ThreadsPerProcess, threads_peak),
+        (Limit::OpenFilesPerProcess, files_peak),
+        (Limit::IpcEndpoints, endpoints),
+    ] {
+        out.push_str(&format!(
+            "{:<21} {:<10} {}\n",
+            limit.name(),
+            current,
+            get(limit)
+        ));
+    }
+    out
+}
+
+/// Format `/proc/<pid>/limits` for `process`. The open file maximum is the
+/// process's own `RLIMIT_NOFILE` where that is lower.
+pub fn format_process_limits(process: &crate::process::Process) -> String {
+    use crate::process::rlimit::RLIMIT_NOFILE;
+
+    let mut out = String::from("Resource              Current    Max\n");
+    for (limit, current, max) in [
+        (
+            Limit::ThreadsPerProcess,
+            process.thread_count(),
+            get(Limit::ThreadsPerProcess),
+        ),
+        (
+            Limit::OpenFilesPerProcess,
+            process.file_table.lock().count_open(),
+            process.rlimits.soft(RLIMIT_NOFILE) as usize,
+        ),
+    ] {
+        out.push_str(&format!("{:<21} {:<10} {}\n", limit.name(), current, max));
+    }
+    out
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_scale_with_memory() {
+        let mib = 1024 * 1024;
+        assert_eq!(Limit::Processes.default_for_memory(256 * mib), 1024);
+        assert_eq!(Limit::ThreadsPerProcess.default_for_memory(256 * mib), 256);
+        assert_eq!(
+            Limit::OpenFilesPerProcess.default_for_memory(256 * mib),
+            1024
+        );
+        assert_eq!(Limit::IpcEndpoints.default_for_memory(256 * mib), 4096);
+        // Small machines get the floor, huge ones the ceiling
+        assert_eq!(Limit::Processes.default_for_memory(8 * mib), 64);
+        assert_eq!(
+            Limit::Processes.default_for_memory(1024 * 1024 * mib),
+            Limit::Processes.ceiling()
+        );
+    }
+
+    #[test]
+    fn test_name_round_trip() {
+        for limit in Limit::ALL {
+            assert_eq!(Limit::from_name(limit.name()), Some(limit));
+        }
+        assert_eq!(Limit::from_name("max_bogus"), None);
+    }
+
+    #[test]
+    fn test_configured_cmdline_override_and_clamp() {
+        // Works on computed values only: other tests read the global limits
+        let mib = 1024 * 1024;
+        let cmdline = "quiet limits.max_processes=300 limits.max_open_files=999999999";
+        assert_eq!(Limit::Processes.configured(512 * mib, cmdline), 300);
+        assert_eq!(
+            Limit::OpenFilesPerProcess.configured(512 * mib, cmdline),
+            Limit::OpenFilesPerProcess.ceiling()
+        );
+        assert_eq!(Limit::ThreadsPerProcess.configured(512 * mib, cmdline), 512);
+        assert_eq!(Limit::IpcEndpoints.bounded(0), 1);
+        assert!(set_by_name("max_threads", "x").is_err());
+    }
+}
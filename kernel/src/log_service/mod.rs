@@ -0,0 +1,751 @@
+//! Structured kernel log service
+//!
+//! Keeps a heap-free record of kernel messages in per-CPU circular buffers.
+//! Each entry carries a global sequence number, a timestamp, the CPU that
+//! logged it, a severity level, a subsystem tag, and a fixed-length message.
+//! Readers merge the per-CPU buffers back into one stream ordered by
+//! sequence number, which is what `dmesg` (the shell builtin and the
+//! `SYS_DMESG` syscall) shows.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! klog!(Info, "sched", "scheduler initialized on {} CPUs", n);
+//! klog_ratelimited!(Warn, "net", "dropping packet from {}", addr);
+//! log_service::klog(LogLevel::Info, "sched", "scheduler initialized");
+//! ```
+//!
+//! The buffers are statically allocated, so logging works from the first
+//! instruction of boot, before the heap exists. [`log_init`] only applies
+//! the `loglevel=` / `quiet` command line parameters.
+//!
+//! Messages at or above the console level (see [`set_console_level`]) are
+//! also echoed to the console as `[TAG] message`. Entries are handed to
+//! [`syslog`] for forwarding to a remote collector, when one is configured.
+//!
+//! Each buffer holds up to [`LOG_BUFFER_CAPACITY`] entries. Once full it
+//! wraps around and overwrites the oldest entries. A writer never waits for
+//! a buffer lock: if the buffer is busy (a reader is copying it, or an
+//! interrupt arrived while the same CPU was logging) the entry is dropped
+//! from the buffer and counted in [`log_dropped`], but still echoed and
+//! forwarded.
+
+// Log service module
+
+// Forwarding needs the network stack and the TOML parser from pkg
+#[cfg(all(feature = "net", feature = "pkg"))]
+pub mod syslog;
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Write},
+    sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering},
+};
+
+use spin::{Mutex, MutexGuard};
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// Maximum number of log entries each per-CPU buffer can hold.
+const LOG_BUFFER_CAPACITY: usize = 256;
+
+/// Number of per-CPU buffers. CPUs with higher IDs share buffers modulo this.
+const LOG_CPUS: usize = crate::sched::smp::MAX_CPUS;
+
+/// Maximum length (in bytes) of a log message stored in a [`LogEntry`].
+const LOG_MESSAGE_MAX_LEN: usize = 128;
+
+/// Maximum length (in bytes) of the subsystem tag in a [`LogEntry`].
+const LOG_SUBSYSTEM_MAX_LEN: usize = 16;
+
+/// Console level used until the command line says otherwise.
+const DEFAULT_CONSOLE_LEVEL: LogLevel = LogLevel::Info;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Severity levels for kernel log messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum LogLevel {
+    /// Unrecoverable or critical errors.
+    Error = 0,
+    /// Conditions that may indicate a problem.
+    Warn = 1,
+    /// Normal operational messages.
+    Info = 2,
+    /// Verbose diagnostic output.
+    Debug = 3,
+    /// Very detailed tracing information.
+    Trace = 4,
+}
+
+impl LogLevel {
+    /// Short lowercase name, as shown by `dmesg`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Error => "err",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+
+    /// Level for a numeric value, saturating at [`LogLevel::Trace`].
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Error,
+            1 => Self::Warn,
+            2 => Self::Info,
+            3 => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+
+    /// Parse a level name (`err`, `warn`, ...) or number (`0`-`4`).
+    pub fn parse(text: &str) -> Option<Self> {
+        Some(match text {
+            "err" | "error" => Self::Error,
+            "warn" | "warning" => Self::Warn,
+            "info" => Self::Info,
+            "debug" => Self::Debug,
+            "trace" => Self::Trace,
+            _ => return text.parse::<u8>().ok().map(Self::from_u8),
+        })
+    }
+}
+
+/// A single structured log entry.
+///
+/// All fields are stored inline with fixed-size arrays so that the entry
+/// can live in a static circular buffer without heap allocation.
+#[derive(Clone)]
+pub struct LogEntry {
+    /// Global sequence number; orders entries across CPUs.
+    pub seq: u64,
+    /// Milliseconds since boot (via `arch::timer::get_timestamp_ms`).
+    pub timestamp_ms: u64,
+    /// CPU that logged the entry.
+    pub cpu: u8,
+    /// Severity of the message.
+    pub level: LogLevel,
+    /// Short subsystem identifier (e.g. `"sched"`, `"mm"`, `"ipc"`).
+    /// Stored as a fixed-size byte array with the actual length tracked
+    /// separately.
+    subsystem_buf: [u8; LOG_SUBSYSTEM_MAX_LEN],
+    subsystem_len: u8,
+    /// The log message text, truncated to [`LOG_MESSAGE_MAX_LEN`] bytes.
+    message_buf: [u8; LOG_MESSAGE_MAX_LEN],
+    message_len: u8,
+}
+
+impl LogEntry {
+    /// Create a zeroed, empty entry (used to initialize the buffer).
+    const fn empty() -> Self {
+        Self {
+            seq: 0,
+            timestamp_ms: 0,
+            cpu: 0,
+            level: LogLevel::Trace,
+            subsystem_buf: [0u8; LOG_SUBSYSTEM_MAX_LEN],
+            subsystem_len: 0,
+            message_buf: [0u8; LOG_MESSAGE_MAX_LEN],
+            message_len: 0,
+        }
+    }
+
+    /// Build an entry, truncating `subsystem` and `message` on character
+    /// boundaries.
+    fn new(
+        seq: u64,
+        timestamp_ms: u64,
+        cpu: u8,
+        level: LogLevel,
+        subsystem: &str,
+        message: &str,
+    ) -> Self {
+        let mut entry = Self::empty();
+        entry.seq = seq;
+        entry.timestamp_ms = timestamp_ms;
+        entry.cpu = cpu;
+        entry.level = level;
+
+        let subsystem = truncate(subsystem, LOG_SUBSYSTEM_MAX_LEN);
+        entry.subsystem_buf[..subsystem.len()].copy_from_slice(subsystem.as_bytes());
+        entry.subsystem_len = subsystem.len() as u8;
+
+        let message = truncate(message, LOG_MESSAGE_MAX_LEN);
+        entry.message_buf[..message.len()].copy_from_slice(message.as_bytes());
+        entry.message_len = message.len() as u8;
+        entry
+    }
+
+    /// Return the subsystem tag as a `&str`.
+    pub fn subsystem(&self) -> &str {
+        let len = self.subsystem_len as usize;
+        // SAFETY/invariant: subsystem_len is always set from a valid UTF-8
+        // source (an incoming &str) and capped at LOG_SUBSYSTEM_MAX_LEN.
+        core::str::from_utf8(&self.subsystem_buf[..len]).unwrap_or("")
+    }
+
+    /// Return the message text as a `&str`.
+    pub fn message(&self) -> &str {
+        let len = self.message_len as usize;
+        core::str::from_utf8(&self.message_buf[..len]).unwrap_or("")
+    }
+}
+
+/// Longest prefix of `text` that fits in `max` bytes without splitting a
+/// character.
+fn truncate(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+// ---------------------------------------------------------------------------
+// Message formatting
+// ---------------------------------------------------------------------------
+
+/// Fixed-size, truncating [`fmt::Write`] target used to format a message
+/// without touching the heap.
+pub struct LineBuf {
+    buf: [u8; LOG_MESSAGE_MAX_LEN],
+    len: usize,
+}
+
+impl LineBuf {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0u8; LOG_MESSAGE_MAX_LEN],
+            len: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only whole characters are ever appended.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl Default for LineBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for LineBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let s = truncate(s, LOG_MESSAGE_MAX_LEN - self.len);
+        self.buf[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Ok(())
+    }
+}
+
+/// Append `entry` to `out` as one `dmesg` line:
+/// `[    1.234] info  bootstrap: message`.
+pub fn format_entry(entry: &LogEntry, out: &mut impl Write) {
+    let _ = writeln!(
+        out,
+        "[{:>5}.{:03}] {:<5} {}: {}",
+        entry.timestamp_ms / 1000,
+        entry.timestamp_ms % 1000,
+        entry.level.name(),
+        entry.subsystem(),
+        entry.message()
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Circular buffer
+// ---------------------------------------------------------------------------
+
+/// Fixed-size circular buffer of [`LogEntry`] items.
+///
+/// Uses head/tail indices with a count to distinguish empty from full.
+struct LogBuffer {
+    entries: [LogEntry; LOG_BUFFER_CAPACITY],
+    /// Index of the next slot to write.
+    head: usize,
+    /// Total number of valid entries (capped at `LOG_BUFFER_CAPACITY`).
+    count: usize,
+}
+
+impl LogBuffer {
+    /// Create a new empty buffer.
+    const fn new() -> Self {
+        // LogEntry is Clone but not Copy, so repeat a const instead.
+        const EMPTY: LogEntry = LogEntry::empty();
+        Self {
+            entries: [EMPTY; LOG_BUFFER_CAPACITY],
+            head: 0,
+            count: 0,
+        }
+    }
+
+    /// Append a log entry, overwriting the oldest if full.
+    fn push(&mut self, entry: LogEntry) {
+        self.entries[self.head] = entry;
+        self.head = (self.head + 1) % LOG_BUFFER_CAPACITY;
+        if self.count < LOG_BUFFER_CAPACITY {
+            self.count += 1;
+        }
+    }
+
+    /// Number of entries currently stored.
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Clear all entries.
+    fn clear(&mut self) {
+        self.head = 0;
+        self.count = 0;
+    }
+
+    /// Return the tail index (oldest entry).
+    fn tail(&self) -> usize {
+        if self.count < LOG_BUFFER_CAPACITY {
+            0
+        } else {
+            self.head // when full, head == tail (oldest)
+        }
+    }
+
+    /// Get the entry at logical index `i` (0 = oldest).
+    ///
+    /// Returns `None` if `i >= count`.
+    fn get(&self, i: usize) -> Option<&LogEntry> {
+        if i >= self.count {
+            return None;
+        }
+        let physical = (self.tail() + i) % LOG_BUFFER_CAPACITY;
+        Some(&self.entries[physical])
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Rate limiting
+// ---------------------------------------------------------------------------
+
+/// Per-callsite rate limit: at most `burst` messages per `interval_ms`.
+///
+/// Used through [`klog_ratelimited!`], which keeps one `RateLimit` in a
+/// static per call site. Messages over the limit are counted, and the count
+/// is reported once the next window opens.
+pub struct RateLimit {
+    interval_ms: u64,
+    burst: u32,
+    window_start: AtomicU64,
+    printed: AtomicU32,
+    suppressed: AtomicU32,
+}
+
+impl RateLimit {
+    /// Default interval used by [`klog_ratelimited!`].
+    pub const DEFAULT_INTERVAL_MS: u64 = 5000;
+    /// Default burst used by [`klog_ratelimited!`].
+    pub const DEFAULT_BURST: u32 = 10;
+
+    pub const fn new(interval_ms: u64, burst: u32) -> Self {
+        Self {
+            interval_ms,
+            burst,
+            window_start: AtomicU64::new(0),
+            printed: AtomicU32::new(0),
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// Whether a message may be logged now. On success returns how many
+    /// messages were suppressed since the previous window.
+    pub fn check(&self) -> Option<u32> {
+        self.check_at(crate::arch::timer::get_timestamp_ms())
+    }
+
+    fn check_at(&self, now_ms: u64) -> Option<u32> {
+        let start = self.window_start.load(Ordering::Relaxed);
+        let mut missed = 0;
+        if now_ms.saturating_sub(start) >= self.interval_ms
+            && self
+                .window_start
+                .compare_exchange(start, now_ms, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.printed.store(0, Ordering::Relaxed);
+            missed = self.suppressed.swap(0, Ordering::Relaxed);
+        }
+
+        if self.printed.fetch_add(1, Ordering::Relaxed) < self.burst {
+            Some(missed)
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global state
+// ---------------------------------------------------------------------------
+
+static LOG_BUFFERS: [Mutex<LogBuffer>; LOG_CPUS] =
+    [const { Mutex::new(LogBuffer::new()) }; LOG_CPUS];
+
+/// Next sequence number to hand out.
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Entries that could not be buffered because the buffer was busy.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Most verbose level echoed to the console.
+static CONSOLE_LEVEL: AtomicU8 = AtomicU8::new(DEFAULT_CONSOLE_LEVEL as u8);
+
+/// Store an entry in the current CPU's buffer, returning a copy.
+fn record(level: LogLevel, subsystem: &str, message: &str) -> LogEntry {
+    let cpu = crate::sched::smp::current_cpu_id();
+    let entry = LogEntry::new(
+        NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+        crate::arch::timer::get_timestamp_ms(),
+        cpu,
+        level,
+        subsystem,
+        message,
+    );
+    match LOG_BUFFERS[cpu as usize % LOG_CPUS].try_lock() {
+        Some(mut buffer) => buffer.push(entry.clone()),
+        None => {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    entry
+}
+
+/// Echo an entry to the console as `[TAG] message`.
+fn echo(entry: &LogEntry) {
+    let mut tag = [0u8; LOG_SUBSYSTEM_MAX_LEN];
+    let len = entry.subsystem_len as usize;
+    tag[..len].copy_from_slice(&entry.subsystem_buf[..len]);
+    tag[..len].make_ascii_uppercase();
+
+    crate::kprint_rt!("[");
+    crate::kprint_rt!(core::str::from_utf8(&tag[..len]).unwrap_or(""));
+    crate::kprint_rt!("] ");
+    crate::kprint_rt!(entry.message());
+    crate::kprint_rt!("\n");
+}
+
+/// Snapshot of every buffered entry, ordered by sequence number.
+fn collect() -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    for buffer in LOG_BUFFERS.iter() {
+        let buffer = buffer.lock();
+        entries.reserve(buffer.len());
+        for i in 0..buffer.len() {
+            if let Some(entry) = buffer.get(i) {
+                entries.push(entry.clone());
+            }
+        }
+    }
+    entries.sort_unstable_by_key(|entry| entry.seq);
+    entries
+}
+
+/// Write the last `lines` entries of `buffers`, merged by sequence number,
+/// without allocating.
+fn write_tail(buffers: &[Option<&LogBuffer>], lines: usize, out: &mut impl Write) {
+    // Walk back from the newest entries to find where the tail starts in
+    // each buffer, then merge forward from there.
+    let mut pos = [0usize; LOG_CPUS];
+    for (pos, buffer) in pos.iter_mut().zip(buffers) {
+        *pos = buffer.map_or(0, |buffer| buffer.len());
+    }
+    for _ in 0..lines {
+        let newest = buffers
+            .iter()
+            .zip(&pos)
+            .enumerate()
+            .filter_map(|(i, (buffer, &pos))| {
+                Some((buffer.as_ref()?.get(pos.checked_sub(1)?)?.seq, i))
+            })
+            .max();
+        match newest {
+            Some((_, i)) => pos[i] -= 1,
+            None => break,
+        }
+    }
+    loop {
+        let oldest = buffers
+            .iter()
+            .zip(&pos)
+            .enumerate()
+            .filter_map(|(i, (buffer, &pos))| Some((buffer.as_ref()?.get(pos)?, i)))
+            .min_by_key(|(entry, _)| entry.seq);
+        match oldest {
+            Some((entry, i)) => {
+                format_entry(entry, out);
+                pos[i] += 1;
+            }
+            None => break,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+crate::kernel_param! {
+    /// Console log level: a name such as `warn` or a number 0-4.
+    static LOGLEVEL: LogLevel = DEFAULT_CONSOLE_LEVEL, "loglevel", on_set = |level| set_console_level(*level);
+}
+
+crate::kernel_param! {
+    /// Only echo warnings and errors to the console (unless `loglevel=` is given).
+    static QUIET: bool = false, "quiet";
+}
+
+impl crate::utils::cmdline::ParamValue for LogLevel {
+    fn parse_param(text: &str) -> Option<Self> {
+        Self::parse(text)
+    }
+
+    fn format_param(&self) -> String {
+        String::from(self.name())
+    }
+}
+
+/// Apply the log command line parameters.
+///
+/// `loglevel=<level>` sets the console level (a name such as `warn` or a
+/// number 0-4); a bare `quiet` lowers it to warnings. Needs the heap, so it
+/// runs after memory management is up. Logging itself works before this.
+pub fn log_init() {
+    if LOGLEVEL.is_set() {
+        set_console_level(LOGLEVEL.get());
+    } else if QUIET.get() {
+        set_console_level(LogLevel::Warn);
+    }
+}
+
+/// Set the most verbose level that is echoed to the console.
+pub fn set_console_level(level: LogLevel) {
+    CONSOLE_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// The most verbose level that is echoed to the console.
+pub fn console_level() -> LogLevel {
+    LogLevel::from_u8(CONSOLE_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Record a structured log entry, echo it to the console if its level is
+/// enabled there, and forward it to syslog.
+pub fn klog(level: LogLevel, subsystem: &str, message: &str) {
+    let entry = record(level, subsystem, message);
+    if level <= console_level() {
+        echo(&entry);
+    }
+    #[cfg(all(feature = "net", feature = "pkg"))]
+    syslog::forward(&entry);
+}
+
+/// Format and log a message. Backend of [`klog!`].
+pub fn klog_fmt(level: LogLevel, subsystem: &str, args: fmt::Arguments) {
+    let mut line = LineBuf::new();
+    let _ = line.write_fmt(args);
+    klog(level, subsystem, line.as_str());
+}
+
+/// Log through a rate limit, first noting how many messages it suppressed.
+/// Backend of [`klog_ratelimited!`].
+pub fn klog_limited(limit: &RateLimit, level: LogLevel, subsystem: &str, args: fmt::Arguments) {
+    if let Some(missed) = limit.check() {
+        if missed > 0 {
+            klog_fmt(
+                level,
+                subsystem,
+                format_args!("{} messages suppressed", missed),
+            );
+        }
+        klog_fmt(level, subsystem, args);
+    }
+}
+
+/// Iterate over all buffered log entries from oldest to newest, calling `f`
+/// for each. Returns the number of entries visited.
+pub fn log_drain<F: FnMut(&LogEntry)>(mut f: F) -> usize {
+    let entries = collect();
+    for entry in &entries {
+        f(entry);
+    }
+    entries.len()
+}
+
+/// Render the buffered entries at or below `max_level` as `dmesg` text.
+pub fn log_read(max_level: LogLevel) -> String {
+    let mut out = String::new();
+    for entry in collect().iter().filter(|e| e.level <= max_level) {
+        format_entry(entry, &mut out);
+    }
+    out
+}
+
+/// Write the last `lines` buffered entries to `out` as `dmesg` text.
+///
+/// Neither allocates nor waits for a lock, so it is safe on the panic path;
+/// a buffer that is busy is left out.
+pub fn log_tail(lines: usize, out: &mut impl Write) {
+    let guards: [Option<MutexGuard<'_, LogBuffer>>; LOG_CPUS] =
+        core::array::from_fn(|cpu| LOG_BUFFERS[cpu].try_lock());
+    let mut buffers: [Option<&LogBuffer>; LOG_CPUS] = [None; LOG_CPUS];
+    for (buffer, guard) in buffers.iter_mut().zip(&guards) {
+        *buffer = guard.as_deref();
+    }
+    write_tail(&buffers, lines, out);
+}
+
+/// Return the number of entries currently in the log buffers.
+pub fn log_count() -> usize {
+    LOG_BUFFERS.iter().map(|buffer| buffer.lock().len()).sum()
+}
+
+/// Number of entries lost because their buffer was busy.
+pub fn log_dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Clear all log entries.
+pub fn log_clear() {
+    for buffer in LOG_BUFFERS.iter() {
+        buffer.lock().clear();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Macros
+// ---------------------------------------------------------------------------
+
+/// Log a formatted message: `klog!(Info, "sched", "{} CPUs online", n)`.
+///
+/// The first argument names a [`LogLevel`] variant, the second is the
+/// subsystem tag.
+#[macro_export]
+macro_rules! klog {
+    ($level:ident, $subsystem:expr, $msg:literal) => {
+        $crate::log_service::klog(
+            $crate::log_service::LogLevel::$level,
+            $subsystem,
+            $msg,
+        )
+    };
+    ($level:ident, $subsystem:expr, $($arg:tt)+) => {
+        $crate::log_service::klog_fmt(
+            $crate::log_service::LogLevel::$level,
+            $subsystem,
+            format_args!($($arg)+),
+        )
+    };
+}
+
+/// Like [`klog!`], but limited to a burst of messages per interval at each
+/// call site.
+#[macro_export]
+macro_rules! klog_ratelimited {
+    ($level:ident, $subsystem:expr, $($arg:tt)+) => {{
+        static LIMIT: $crate::log_service::RateLimit = $crate::log_service::RateLimit::new(
+            $crate::log_service::RateLimit::DEFAULT_INTERVAL_MS,
+            $crate::log_service::RateLimit::DEFAULT_BURST,
+        );
+        $crate::log_service::klog_limited(
+            &LIMIT,
+            $crate::log_service::LogLevel::$level,
+            $subsystem,
+            format_args!($($arg)+),
+        )
+    }};
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_parse() {
+        assert_eq!(LogLevel::parse("warn"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("error"), Some(LogLevel::Error));
+        assert_eq!(LogLevel::parse("3"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::parse("9"), Some(LogLevel::Trace));
+        assert_eq!(LogLevel::parse("loud"), None);
+    }
+
+    #[test]
+    fn test_buffer_wraps() {
+        let mut buffer = LogBuffer::new();
+        for seq in 0..(LOG_BUFFER_CAPACITY as u64 + 3) {
+            buffer.push(LogEntry::new(seq, 0, 0, LogLevel::Info, "t", "m"));
+        }
+        assert_eq!(buffer.len(), LOG_BUFFER_CAPACITY);
+        assert_eq!(buffer.get(0).unwrap().seq, 3);
+        assert!(buffer.get(LOG_BUFFER_CAPACITY).is_none());
+    }
+
+    #[test]
+    fn test_entry_truncates_on_char_boundary() {
+        let long = "é".repeat(LOG_MESSAGE_MAX_LEN);
+        let entry = LogEntry::new(0, 0, 0, LogLevel::Info, "subsystem-too-long", &long);
+        assert_eq!(entry.subsystem(), "subsystem-too-lo");
+        assert_eq!(entry.message().len(), LOG_MESSAGE_MAX_LEN);
+
+        let mut line = LineBuf::new();
+        let _ = write!(line, "{}", long);
+        assert_eq!(line.as_str(), entry.message());
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let entry = LogEntry::new(0, 12_345, 0, LogLevel::Warn, "net", "link down");
+        let mut out = String::new();
+        format_entry(&entry, &mut out);
+        assert_eq!(out, "[   12.345] warn  net: link down\n");
+    }
+
+    #[test]
+    fn test_tail_merges_buffers() {
+        let mut cpu0 = LogBuffer::new();
+        let mut cpu1 = LogBuffer::new();
+        for seq in 0..6u64 {
+            let buffer = if seq % 3 == 0 { &mut cpu1 } else { &mut cpu0 };
+            let message = alloc::format!("m{}", seq);
+            buffer.push(LogEntry::new(seq, seq, 0, LogLevel::Info, "t", &message));
+        }
+        let mut out = String::new();
+        write_tail(&[Some(&cpu0), None, Some(&cpu1)], 4, &mut out);
+        let messages: Vec<&str> = out.lines().map(|l| l.rsplit(' ').next().unwrap()).collect();
+        assert_eq!(messages, ["m2", "m3", "m4", "m5"]);
+
+        let mut out = String::new();
+        write_tail(&[Some(&cpu0), Some(&cpu1)], 100, &mut out);
+        assert_eq!(out.lines().count(), 6);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limit = RateLimit::new(1000, 2);
+        assert_eq!(limit.check_at(1000), Some(0));
+        assert_eq!(limit.check_at(1100), Some(0));
+        assert_eq!(limit.check_at(1200), None);
+        assert_eq!(limit.check_at(1300), None);
+        assert_eq!(limit.check_at(2100), Some(2));
+        assert_eq!(limit.check_at(2200), Some(0));
+    }
+}
@@ -0,0 +1,888 @@
+//! Remote syslog forwarding
+//!
+//! Forwards kernel log entries to a remote collector as RFC 5424 messages,
+//! over UDP (RFC 5426) or TCP with octet-counting framing (RFC 6587).
+//! Messages that cannot be sent -- no interface address yet, socket errors
+//! -- are held in a bounded local spool and replayed oldest-first once the
+//! collector is reachable again.
+//!
+//! Configuration is read from [`CONFIG_PATH`]:
+//!
+//! ```toml
+//! [syslog]
+//! server = "10.0.2.2:514"
+//! transport = "udp"            # or "tcp"
+//! hostname = "node-17"         # defaults to "veridian"
+//! rules = ["kern.warning", "auth.*", "*.err"]
+//! spool = 256                  # messages held while the collector is down
+//! ```
+//!
+//! Each rule is `facility.severity`, where either side may be `*`. An entry
+//! is forwarded when any rule matches its facility and it is at least as
+//! severe as the rule's threshold; a severity of `none` excludes that
+//! facility regardless of other rules. With no rules, everything is sent.
+
+use alloc::{collections::VecDeque, format, string::String, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use spin::Mutex;
+
+use super::{LogEntry, LogLevel};
+use crate::{
+    error::KernelError,
+    net::{
+        socket::{self, SocketDomain, SocketProtocol, SocketType},
+        Ipv4Address, SocketAddr,
+    },
+    pkg::toml_parser::{self, TomlValue},
+};
+
+/// Configuration file read by [`load_config`].
+pub const CONFIG_PATH: &str = "/etc/syslog.toml";
+
+/// Well-known syslog port.
+pub const DEFAULT_PORT: u16 = 514;
+
+/// Default number of messages held while the collector is unreachable.
+const DEFAULT_SPOOL_LIMIT: usize = 256;
+
+/// HOSTNAME used when the config does not set one (the `uname` nodename).
+const DEFAULT_HOSTNAME: &str = "veridian";
+
+/// APP-NAME field of every message we emit.
+const APP_NAME: &str = "kernel";
+
+// ---------------------------------------------------------------------------
+// Facilities and severities
+// ---------------------------------------------------------------------------
+
+/// RFC 5424 facility codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Facility {
+    Kern = 0,
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+impl Facility {
+    const ALL: [Facility; 20] = [
+        Self::Kern,
+        Self::User,
+        Self::Mail,
+        Self::Daemon,
+        Self::Auth,
+        Self::Syslog,
+        Self::Lpr,
+        Self::News,
+        Self::Uucp,
+        Self::Cron,
+        Self::AuthPriv,
+        Self::Ftp,
+        Self::Local0,
+        Self::Local1,
+        Self::Local2,
+        Self::Local3,
+        Self::Local4,
+        Self::Local5,
+        Self::Local6,
+        Self::Local7,
+    ];
+
+    /// Conventional lowercase name, as used in rules.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kern => "kern",
+            Self::User => "user",
+            Self::Mail => "mail",
+            Self::Daemon => "daemon",
+            Self::Auth => "auth",
+            Self::Syslog => "syslog",
+            Self::Lpr => "lpr",
+            Self::News => "news",
+            Self::Uucp => "uucp",
+            Self::Cron => "cron",
+            Self::AuthPriv => "authpriv",
+            Self::Ftp => "ftp",
+            Self::Local0 => "local0",
+            Self::Local1 => "local1",
+            Self::Local2 => "local2",
+            Self::Local3 => "local3",
+            Self::Local4 => "local4",
+            Self::Local5 => "local5",
+            Self::Local6 => "local6",
+            Self::Local7 => "local7",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|f| f.name() == name)
+    }
+
+    /// Facility a kernel subsystem tag is reported under.
+    pub fn for_subsystem(subsystem: &str) -> Self {
+        match subsystem {
+            "auth" | "login" | "security" | "cred" => Self::Auth,
+            "cron" => Self::Cron,
+            "init" | "services" | "svc" => Self::Daemon,
+            "syslog" => Self::Syslog,
+            _ => Self::Kern,
+        }
+    }
+}
+
+/// RFC 5424 severity codes (lower is more severe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Severity {
+    Emergency = 0,
+    Alert = 1,
+    Critical = 2,
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Informational = 6,
+    Debug = 7,
+}
+
+impl Severity {
+    /// Parse a severity name, accepting the common syslog.conf aliases.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "emerg" | "panic" => Self::Emergency,
+            "alert" => Self::Alert,
+            "crit" => Self::Critical,
+            "err" | "error" => Self::Error,
+            "warning" | "warn" => Self::Warning,
+            "notice" => Self::Notice,
+            "info" => Self::Informational,
+            "debug" => Self::Debug,
+            _ => return None,
+        })
+    }
+}
+
+impl From<LogLevel> for Severity {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => Self::Error,
+            LogLevel::Warn => Self::Warning,
+            LogLevel::Info => Self::Informational,
+            LogLevel::Debug | LogLevel::Trace => Self::Debug,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Forwarding rules
+// ---------------------------------------------------------------------------
+
+/// A single `facility.severity` selector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// `None` matches every facility.
+    pub facility: Option<Facility>,
+    /// Least severe level forwarded; `None` means the facility is excluded.
+    pub threshold: Option<Severity>,
+}
+
+impl Rule {
+    pub fn parse(selector: &str) -> Result<Self, KernelError> {
+        let invalid = KernelError::InvalidArgument {
+            name: "syslog_rule",
+            value: "expected facility.severity",
+        };
+        let (facility, severity) = selector.trim().split_once('.').ok_or(invalid)?;
+
+        let facility = match facility {
+            "*" => None,
+            name => Some(Facility::from_name(name).ok_or(invalid)?),
+        };
+        let threshold = match severity {
+            "*" => Some(Severity::Debug),
+            "none" => None,
+            name => Some(Severity::from_name(name).ok_or(invalid)?),
+        };
+
+        Ok(Self {
+            facility,
+            threshold,
+        })
+    }
+
+    fn covers(&self, facility: Facility) -> bool {
+        self.facility.is_none_or(|f| f == facility)
+    }
+}
+
+/// Whether an entry with the given facility and severity passes `rules`.
+pub fn rules_admit(rules: &[Rule], facility: Facility, severity: Severity) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+    let mut admitted = false;
+    for rule in rules.iter().filter(|r| r.covers(facility)) {
+        match rule.threshold {
+            None => return false,
+            Some(threshold) => admitted |= severity <= threshold,
+        }
+    }
+    admitted
+}
+
+// ---------------------------------------------------------------------------
+// Configuration
+// ---------------------------------------------------------------------------
+
+/// Transport used to reach the collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// Parsed `[syslog]` configuration.
+#[derive(Debug, Clone)]
+pub struct SyslogConfig {
+    pub server: SocketAddr,
+    pub transport: Transport,
+    pub hostname: String,
+    pub rules: Vec<Rule>,
+    pub spool_limit: usize,
+}
+
+impl SyslogConfig {
+    pub fn new(server: SocketAddr, transport: Transport) -> Self {
+        Self {
+            server,
+            transport,
+            hostname: String::from(DEFAULT_HOSTNAME),
+            rules: Vec::new(),
+            spool_limit: DEFAULT_SPOOL_LIMIT,
+        }
+    }
+
+    /// Parse the TOML configuration file contents.
+    pub fn parse(text: &str) -> Result<Self, KernelError> {
+        let root = toml_parser::parse_toml(text)?;
+        let section =
+            root.get("syslog")
+                .and_then(|v| v.as_table())
+                .ok_or(KernelError::InvalidArgument {
+                    name: "syslog_config",
+                    value: "missing [syslog] section",
+                })?;
+
+        let server =
+            section
+                .get("server")
+                .and_then(|v| v.as_str())
+                .ok_or(KernelError::InvalidArgument {
+                    name: "syslog_config",
+                    value: "missing server",
+                })?;
+        let server = parse_server(server)?;
+
+        let transport = match section.get("transport").and_then(|v| v.as_str()) {
+            None | Some("udp") => Transport::Udp,
+            Some("tcp") => Transport::Tcp,
+            Some(_) => {
+                return Err(KernelError::InvalidArgument {
+                    name: "syslog_transport",
+                    value: "expected udp or tcp",
+                })
+            }
+        };
+
+        let mut config = Self::new(server, transport);
+
+        if let Some(hostname) = section.get("hostname").and_then(|v| v.as_str()) {
+            config.hostname = String::from(hostname);
+        }
+        if let Some(rules) = section.get("rules").and_then(|v| v.as_array()) {
+            for rule in rules {
+                let selector = rule.as_str().ok_or(KernelError::InvalidArgument {
+                    name: "syslog_rule",
+                    value: "not a string",
+                })?;
+                config.rules.push(Rule::parse(selector)?);
+            }
+        }
+        if let Some(limit) = section.get("spool").and_then(TomlValue::as_integer) {
+            config.spool_limit =
+                usize::try_from(limit).map_err(|_| KernelError::InvalidArgument {
+                    name: "syslog_spool",
+                    value: "negative",
+                })?;
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parse `a.b.c.d` or `a.b.c.d:port`.
+fn parse_server(s: &str) -> Result<SocketAddr, KernelError> {
+    let invalid = KernelError::InvalidArgument {
+        name: "syslog_server",
+        value: "expected a.b.c.d[:port]",
+    };
+    let (host, port) = match s.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>().map_err(|_| invalid)?),
+        None => (s, DEFAULT_PORT),
+    };
+
+    let mut octets = [0u8; 4];
+    let mut parts = host.split('.');
+    for octet in octets.iter_mut() {
+        *octet = parts
+            .next()
+            .and_then(|p| p.parse::<u8>().ok())
+            .ok_or(invalid)?;
+    }
+    if parts.next().is_some() {
+        return Err(invalid);
+    }
+
+    Ok(SocketAddr::v4(Ipv4Address(octets), port))
+}
+
+// ---------------------------------------------------------------------------
+// Message formatting
+// ---------------------------------------------------------------------------
+
+/// Render an entry as an RFC 5424 message:
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID SD MSG`.
+///
+/// `epoch_ms` is wall-clock UTC milliseconds, or `None` when the clock is
+/// unknown (rendered as the NILVALUE `-`).
+pub fn format_message(
+    entry: &LogEntry,
+    facility: Facility,
+    hostname: &str,
+    epoch_ms: Option<u64>,
+) -> Vec<u8> {
+    let severity = Severity::from(entry.level);
+    let pri = (facility as u8) * 8 + severity as u8;
+    let timestamp = match epoch_ms {
+        Some(ms) => format_timestamp(ms),
+        None => String::from("-"),
+    };
+    format!(
+        "<{}>1 {} {} {} - {} - {}",
+        pri,
+        timestamp,
+        header_field(hostname),
+        APP_NAME,
+        header_field(entry.subsystem()),
+        entry.message()
+    )
+    .into_bytes()
+}
+
+/// Header fields are printable US-ASCII without spaces; empty becomes `-`.
+fn header_field(s: &str) -> String {
+    let field: String = s
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(48)
+        .collect();
+    if field.is_empty() {
+        String::from("-")
+    } else {
+        field
+    }
+}
+
+/// `YYYY-MM-DDThh:mm:ss.mmmZ` for milliseconds since the Unix epoch.
+fn format_timestamp(epoch_ms: u64) -> String {
+    let secs = epoch_ms / 1000;
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let tod = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        tod / 3600,
+        (tod / 60) % 60,
+        tod % 60,
+        epoch_ms % 1000
+    )
+}
+
+/// Gregorian date for a day count since 1970-01-01 (Hinnant's algorithm).
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Apply transport framing: datagrams carry one message each, TCP streams
+/// use RFC 6587 octet counting (`LEN SP MSG`).
+pub fn frame(transport: Transport, message: &[u8]) -> Vec<u8> {
+    match transport {
+        Transport::Udp => message.to_vec(),
+        Transport::Tcp => {
+            let mut framed = format!("{} ", message.len()).into_bytes();
+            framed.extend_from_slice(message);
+            framed
+        }
+    }
+}
+
+/// Current UTC wall-clock time in milliseconds, if the platform has an RTC.
+fn wall_clock_ms() -> Option<u64> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let local = crate::arch::x86_64::rtc::current_epoch_secs() as i64;
+        let utc = local.saturating_sub(crate::arch::x86_64::rtc::get_timezone_offset());
+        let millis = crate::arch::timer::get_timestamp_ms() % 1000;
+        (utc > 0).then_some(utc as u64 * 1000 + millis)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Forwarder
+// ---------------------------------------------------------------------------
+
+/// Forwarding counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyslogStats {
+    /// Messages delivered to the socket layer.
+    pub sent: u64,
+    /// Messages currently held in the spool.
+    pub spooled: usize,
+    /// Messages discarded because the spool was full.
+    pub dropped: u64,
+}
+
+struct Forwarder {
+    config: SyslogConfig,
+    socket: Option<usize>,
+    spool: VecDeque<Vec<u8>>,
+    sent: u64,
+    dropped: u64,
+}
+
+impl Forwarder {
+    fn new(config: SyslogConfig) -> Self {
+        Self {
+            config,
+            socket: None,
+            spool: VecDeque::new(),
+            sent: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Send `message`, replaying any spooled backlog first so the collector
+    /// sees entries in order. Falls back to spooling on failure.
+    fn submit(&mut self, message: Vec<u8>) {
+        if self.flush().is_err() || self.transmit(&message).is_err() {
+            self.enqueue(message);
+        }
+    }
+
+    /// Drain the spool oldest-first, stopping at the first failure.
+    fn flush(&mut self) -> Result<(), KernelError> {
+        while let Some(message) = self.spool.front() {
+            let message = message.clone();
+            self.transmit(&message)?;
+            self.spool.pop_front();
+        }
+        Ok(())
+    }
+
+    fn enqueue(&mut self, message: Vec<u8>) {
+        if self.config.spool_limit == 0 {
+            self.dropped += 1;
+            return;
+        }
+        while self.spool.len() >= self.config.spool_limit {
+            self.spool.pop_front();
+            self.dropped += 1;
+        }
+        self.spool.push_back(message);
+    }
+
+    fn transmit(&mut self, message: &[u8]) -> Result<(), KernelError> {
+        if !network_ready(&self.config.server) {
+            return Err(KernelError::InvalidState {
+                expected: "configured interface",
+                actual: "no address",
+            });
+        }
+
+        let id = self.connect()?;
+        let framed = frame(self.config.transport, message);
+        match socket::sendto(id, &framed, None) {
+            Ok(_) => {
+                self.sent += 1;
+                Ok(())
+            }
+            Err(e) => {
+                // Reconnect on the next attempt.
+                self.close();
+                Err(e)
+            }
+        }
+    }
+
+    /// Return the collector socket, opening it on first use.
+    fn connect(&mut self) -> Result<usize, KernelError> {
+        if let Some(id) = self.socket {
+            return Ok(id);
+        }
+
+        let (socket_type, protocol) = match self.config.transport {
+            Transport::Udp => (SocketType::Dgram, SocketProtocol::Udp),
+            Transport::Tcp => (SocketType::Stream, SocketProtocol::Tcp),
+        };
+        let id = socket::create_socket(SocketDomain::Inet, socket_type, protocol)?;
+        let server = self.config.server;
+        if let Err(e) = socket::with_socket_mut(id, |s| s.connect(server))? {
+            let _ = socket::close_socket(id);
+            return Err(e);
+        }
+
+        self.socket = Some(id);
+        Ok(id)
+    }
+
+    fn close(&mut self) {
+        if let Some(id) = self.socket.take() {
+            let _ = socket::close_socket(id);
+        }
+    }
+
+    /// Send `entry` if it passes the configured rules.
+    fn forward(&mut self, entry: &LogEntry) {
+        let facility = Facility::for_subsystem(entry.subsystem());
+        let severity = Severity::from(entry.level);
+        if rules_admit(&self.config.rules, facility, severity) {
+            let message = format_message(entry, facility, &self.config.hostname, wall_clock_ms());
+            self.submit(message);
+        }
+    }
+
+    fn stats(&self) -> SyslogStats {
+        SyslogStats {
+            sent: self.sent,
+            spooled: self.spool.len(),
+            dropped: self.dropped + LOST.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Whether there is a usable route to `server` (loopback always works).
+fn network_ready(server: &SocketAddr) -> bool {
+    match server.ip() {
+        crate::net::IpAddress::V4(ip) if ip.0[0] == 127 => true,
+        _ => crate::net::ip::get_interface_config().ip_addr != Ipv4Address::UNSPECIFIED,
+    }
+}
+
+static FORWARDER: Mutex<Option<Forwarder>> = Mutex::new(None);
+
+crate::define_per_cpu! {
+    /// Set while this CPU holds [`FORWARDER`]. Anything logged meanwhile --
+    /// by the network stack on this CPU, or on a CPU that finds the
+    /// forwarder busy -- goes to [`PENDING`] instead of waiting for it.
+    static FORWARDING: AtomicBool = AtomicBool::new(false);
+}
+
+/// Entries logged while the forwarder was busy, forwarded by whoever next
+/// holds it. Never held while taking another lock.
+static PENDING: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// Entries lost because [`PENDING`] was full or in use.
+static LOST: AtomicU64 = AtomicU64::new(0);
+
+/// This CPU's [`FORWARDING`] flag, set until dropped.
+struct ForwardingGuard {
+    cpu: usize,
+}
+
+impl ForwardingGuard {
+    /// Set the flag; `None` if this CPU is already forwarding.
+    fn enter() -> Option<Self> {
+        let cpu = crate::sched::percpu::cpu_id();
+        if FORWARDING.on_cpu(cpu).swap(true, Ordering::Acquire) {
+            return None;
+        }
+        Some(Self { cpu })
+    }
+}
+
+impl Drop for ForwardingGuard {
+    fn drop(&mut self) {
+        FORWARDING.on_cpu(self.cpu).store(false, Ordering::Release);
+    }
+}
+
+/// Hold `entry` until the forwarder is free.
+fn defer(entry: &LogEntry) {
+    // try_lock: this may be an interrupt on a CPU that is draining the queue
+    match PENDING.try_lock() {
+        Some(mut pending) if pending.len() < DEFAULT_SPOOL_LIMIT => {
+            pending.push_back(entry.clone())
+        }
+        _ => {
+            LOST.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Run `f` on the forwarder with this CPU's [`FORWARDING`] flag set, so
+/// that nothing logged under the lock waits for it, then forward what was
+/// deferred meanwhile. Entries deferred by that forwarding itself wait for
+/// the next call.
+fn with_forwarder<R>(
+    _guard: &ForwardingGuard,
+    forwarder: &mut Option<Forwarder>,
+    f: impl FnOnce(&mut Option<Forwarder>) -> R,
+) -> R {
+    let result = f(forwarder);
+    let deferred = core::mem::take(&mut *PENDING.lock());
+    if let Some(forwarder) = forwarder.as_mut() {
+        for entry in &deferred {
+            forwarder.forward(entry);
+        }
+    }
+    result
+}
+
+/// Take the forwarder lock for a configuration change or flush.
+fn locked<R>(f: impl FnOnce(&mut Option<Forwarder>) -> R) -> R {
+    // The flag may belong to a task preempted in `forward`; wait for it
+    let guard = loop {
+        match ForwardingGuard::enter() {
+            Some(guard) => break guard,
+            None => core::hint::spin_loop(),
+        }
+    };
+    let mut forwarder = FORWARDER.lock();
+    with_forwarder(&guard, &mut forwarder, f)
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Start forwarding with `config`, replacing any previous configuration.
+///
+/// Messages spooled under the previous configuration are kept.
+pub fn configure(config: SyslogConfig) {
+    locked(|forwarder| {
+        let spool = match forwarder.take() {
+            Some(mut old) => {
+                old.close();
+                core::mem::take(&mut old.spool)
+            }
+            None => VecDeque::new(),
+        };
+        let mut new = Forwarder::new(config);
+        for message in spool {
+            new.enqueue(message);
+        }
+        *forwarder = Some(new);
+    });
+}
+
+/// Stop forwarding and discard the spool.
+pub fn disable() {
+    locked(|forwarder| {
+        if let Some(mut old) = forwarder.take() {
+            old.close();
+        }
+    });
+}
+
+/// Load and apply the configuration file at `path`.
+pub fn load_config(path: &str) -> Result<(), KernelError> {
+    let data = crate::fs::read_file(path)?;
+    let text = core::str::from_utf8(&data).map_err(|_| KernelError::InvalidArgument {
+        name: "syslog_config",
+        value: "not UTF-8",
+    })?;
+    configure(SyslogConfig::parse(text)?);
+    Ok(())
+}
+
+/// Forward a freshly recorded log entry if it passes the configured rules.
+///
+/// Entries logged while this CPU is forwarding, or while another CPU holds
+/// the forwarder, are deferred rather than waiting for it: the network
+/// stack may log while holding locks the forwarding CPU needs.
+pub fn forward(entry: &LogEntry) {
+    let Some(guard) = ForwardingGuard::enter() else {
+        defer(entry);
+        return;
+    };
+    let Some(mut forwarder) = FORWARDER.try_lock() else {
+        defer(entry);
+        return;
+    };
+    with_forwarder(&guard, &mut forwarder, |forwarder| {
+        if let Some(forwarder) = forwarder.as_mut() {
+            forwarder.forward(entry);
+        }
+    });
+}
+
+/// Try to deliver spooled messages now.
+///
+/// Returns the number still spooled, or `None` if forwarding is disabled.
+pub fn flush() -> Option<usize> {
+    locked(|forwarder| {
+        let forwarder = forwarder.as_mut()?;
+        let _ = forwarder.flush();
+        Some(forwarder.spool.len())
+    })
+}
+
+/// Current counters, or `None` if forwarding is disabled.
+pub fn stats() -> Option<SyslogStats> {
+    FORWARDER.lock().as_ref().map(Forwarder::stats)
+}
+
+/// Configured collector address and transport, if forwarding is enabled.
+pub fn target() -> Option<(SocketAddr, Transport)> {
+    FORWARDER
+        .lock()
+        .as_ref()
+        .map(|f| (f.config.server, f.config.transport))
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    fn entry(level: LogLevel, subsystem: &str, message: &str) -> LogEntry {
+        LogEntry::new(0, 0, 0, level, subsystem, message)
+    }
+
+    #[test]
+    fn test_rule_parse() {
+        assert_eq!(
+            Rule::parse("kern.warning").unwrap(),
+            Rule {
+                facility: Some(Facility::Kern),
+                threshold: Some(Severity::Warning),
+            }
+        );
+        assert_eq!(Rule::parse("*.*").unwrap().facility, None);
+        assert_eq!(Rule::parse("auth.none").unwrap().threshold, None);
+        assert!(Rule::parse("kern").is_err());
+        assert!(Rule::parse("bogus.err").is_err());
+        assert!(Rule::parse("kern.loud").is_err());
+    }
+
+    #[test]
+    fn test_rules_admit() {
+        let rules = [
+            Rule::parse("kern.warning").unwrap(),
+            Rule::parse("*.err").unwrap(),
+            Rule::parse("cron.none").unwrap(),
+        ];
+        assert!(rules_admit(&rules, Facility::Kern, Severity::Warning));
+        assert!(!rules_admit(&rules, Facility::Kern, Severity::Notice));
+        assert!(rules_admit(&rules, Facility::Auth, Severity::Error));
+        assert!(!rules_admit(&rules, Facility::Auth, Severity::Warning));
+        assert!(!rules_admit(&rules, Facility::Cron, Severity::Emergency));
+        assert!(rules_admit(&[], Facility::Local7, Severity::Debug));
+    }
+
+    #[test]
+    fn test_config_parse() {
+        let config = SyslogConfig::parse(
+            "[syslog]\nserver = \"10.0.2.2\"\ntransport = \"tcp\"\nhostname = \"node-17\"\nrules \
+             = [\"kern.info\", \"auth.*\"]\nspool = 8\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.server,
+            SocketAddr::v4(Ipv4Address::new(10, 0, 2, 2), DEFAULT_PORT)
+        );
+        assert_eq!(config.transport, Transport::Tcp);
+        assert_eq!(config.hostname, "node-17");
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.spool_limit, 8);
+
+        assert!(SyslogConfig::parse("[syslog]\ntransport = \"udp\"\n").is_err());
+        assert!(SyslogConfig::parse("[syslog]\nserver = \"10.0.2\"\n").is_err());
+        assert!(SyslogConfig::parse("[syslog]\nserver = \"10.0.2.2:99999\"\n").is_err());
+    }
+
+    #[test]
+    fn test_format_message() {
+        let e = entry(LogLevel::Warn, "auth", "bad password for root");
+        let msg = format_message(&e, Facility::Auth, "node 17", Some(1_700_000_000_123));
+        assert_eq!(
+            core::str::from_utf8(&msg).unwrap(),
+            "<36>1 2023-11-14T22:13:20.123Z node17 kernel - auth - bad password for root"
+        );
+
+        let e = entry(LogLevel::Error, "", "oops");
+        let msg = format_message(&e, Facility::Kern, "", None);
+        assert_eq!(
+            core::str::from_utf8(&msg).unwrap(),
+            "<3>1 - - kernel - - - oops"
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_675), (2023, 11, 14));
+    }
+
+    #[test]
+    fn test_frame() {
+        assert_eq!(frame(Transport::Udp, b"<13>1 x"), b"<13>1 x");
+        assert_eq!(frame(Transport::Tcp, b"<13>1 x"), b"7 <13>1 x");
+    }
+
+    #[test]
+    fn test_spool_bounded() {
+        let server = SocketAddr::v4(Ipv4Address::new(10, 0, 2, 2), DEFAULT_PORT);
+        let mut config = SyslogConfig::new(server, Transport::Udp);
+        config.spool_limit = 2;
+        let mut forwarder = Forwarder::new(config);
+        for i in 0..3u8 {
+            forwarder.enqueue(alloc::vec![i]);
+        }
+        assert_eq!(forwarder.spool.len(), 2);
+        assert_eq!(forwarder.spool.front().map(|m| m[0]), Some(1));
+        assert_eq!(forwarder.stats().dropped, 1);
+    }
+}
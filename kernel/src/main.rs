@@ -169,6 +169,9 @@ fn x86_64_kernel_entry(boot_info: &'static mut BootInfo) -> ! {
 #[cfg(not(test))]
 #[panic_handler]
 fn panic(_info: &PanicInfo) -> ! {
+    // Record a crash dump (and print the backtrace) while the state is fresh
+    crash::on_panic(_info);
+
     // Use architecture-specific panic handler
     #[cfg(target_arch = "x86_64")]
     arch::x86_64::entry::arch_panic_handler(_info);
@@ -1,4 +1,4 @@
-//! Image format decoders: PNG, JPEG (baseline DCT), GIF (87a/89a)
+//! Image format decoders: PNG, BMP, QOI, JPEG (baseline DCT), GIF (87a/89a)
 //!
 //! All decoders are `no_std`-compatible, use integer/fixed-point math only
 //! (no floating point), and produce pixel buffers suitable for the desktop
@@ -6,9 +6,8 @@
 //!
 //! # Supported Formats
 //!
-//! - **PNG**: Full critical chunk support (IHDR, PLTE, IDAT, IEND), ancillary
-//!   chunks (tRNS, gAMA), DEFLATE decompression, all 5 filter types, Adam7
-//!   interlacing, color types 0/2/3/4/6, bit depths 1/2/4/8/16
+//! - **PNG, BMP, QOI**: provided by the shared `image-format` crate, which
+//!   userland links as well; [`DecodedImage`] is that crate's `Image`
 //! - **JPEG**: Baseline DCT (SOF0), Huffman entropy coding, integer IDCT,
 //!   YCbCr-to-RGB via fixed-point, chroma subsampling 4:4:4/4:2:2/4:2:0,
 //!   restart intervals
@@ -19,14 +18,13 @@
 
 pub mod gif;
 pub mod jpeg;
-pub mod png;
 
 use alloc::vec::Vec;
 
 // Re-export all public items from submodules
 pub use gif::{decode_gif, DecodedGif, GifDisposal, GifFrame};
+pub use image_format::Image as DecodedImage;
 pub use jpeg::decode_jpeg;
-pub use png::decode_png;
 
 // ============================================================================
 // Common types and helpers
@@ -55,61 +53,30 @@ pub enum ImageCodecError {
     InvalidQuantTable,
 }
 
-/// A decoded image frame with RGBA8888 pixels.
-#[derive(Debug, Clone, PartialEq)]
-pub struct DecodedImage {
-    /// Width in pixels.
-    pub width: u32,
-    /// Height in pixels.
-    pub height: u32,
-    /// RGBA8888 pixel data, row-major, 4 bytes per pixel.
-    pub pixels: Vec<u8>,
-}
-
-impl DecodedImage {
-    /// Create a new image filled with transparent black.
-    pub fn new(width: u32, height: u32) -> Self {
-        let size = (width as usize)
-            .checked_mul(height as usize)
-            .and_then(|n| n.checked_mul(4))
-            .unwrap_or(0);
-        Self {
-            width,
-            height,
-            pixels: alloc::vec![0u8; size],
+impl From<image_format::ImageError> for ImageCodecError {
+    fn from(err: image_format::ImageError) -> Self {
+        use image_format::ImageError;
+        match err {
+            ImageError::TruncatedData => Self::TruncatedData,
+            ImageError::InvalidSignature => Self::InvalidSignature,
+            ImageError::Unsupported => Self::Unsupported,
+            ImageError::CorruptData => Self::CorruptData,
+            ImageError::ChecksumMismatch => Self::ChecksumMismatch,
+            ImageError::InvalidDimensions => Self::InvalidDimensions,
+            ImageError::DecompressionError => Self::DecompressionError,
+            ImageError::InvalidHuffmanTable => Self::InvalidHuffmanTable,
         }
     }
+}
 
-    /// Set a pixel at (x, y). Out-of-bounds writes are silently ignored.
-    #[inline]
-    pub fn set_pixel(&mut self, x: u32, y: u32, r: u8, g: u8, b: u8, a: u8) {
-        if x < self.width && y < self.height {
-            let off = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
-            if off + 3 < self.pixels.len() {
-                self.pixels[off] = r;
-                self.pixels[off + 1] = g;
-                self.pixels[off + 2] = b;
-                self.pixels[off + 3] = a;
-            }
-        }
-    }
+/// Decode a PNG image from raw file data.
+pub fn decode_png(data: &[u8]) -> Result<DecodedImage, ImageCodecError> {
+    Ok(image_format::decode_png(data)?)
+}
 
-    /// Get a pixel at (x, y) as (R, G, B, A).
-    #[inline]
-    pub fn get_pixel(&self, x: u32, y: u32) -> (u8, u8, u8, u8) {
-        if x < self.width && y < self.height {
-            let off = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
-            if off + 3 < self.pixels.len() {
-                return (
-                    self.pixels[off],
-                    self.pixels[off + 1],
-                    self.pixels[off + 2],
-                    self.pixels[off + 3],
-                );
-            }
-        }
-        (0, 0, 0, 0)
-    }
+/// Encode an image as an 8-bit RGB (if fully opaque) or RGBA PNG.
+pub fn encode_png(image: &DecodedImage) -> Result<Vec<u8>, ImageCodecError> {
+    Ok(image_format::encode_png(image)?)
 }
 
 // ---------------------------------------------------------------------------
@@ -162,15 +129,21 @@ pub(crate) fn clamp_u8(v: i32) -> u8 {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageCodecFormat {
     Png,
+    Bmp,
+    Qoi,
     Jpeg,
     Gif,
     Unknown,
 }
 
-/// Detect whether data is PNG, JPEG, or GIF.
+/// Detect whether data is PNG, BMP, QOI, JPEG, or GIF.
 pub fn detect_codec_format(data: &[u8]) -> ImageCodecFormat {
-    if data.len() >= 8 && data[..8] == png::PNG_SIGNATURE {
-        ImageCodecFormat::Png
+    if let Some(format) = image_format::Format::detect(data) {
+        match format {
+            image_format::Format::Png => ImageCodecFormat::Png,
+            image_format::Format::Bmp => ImageCodecFormat::Bmp,
+            image_format::Format::Qoi => ImageCodecFormat::Qoi,
+        }
     } else if data.len() >= 2 && data[0] == 0xFF && data[1] == 0xD8 {
         ImageCodecFormat::Jpeg
     } else if data.len() >= 6 && (&data[..6] == gif::GIF87A || &data[..6] == gif::GIF89A) {
@@ -183,7 +156,9 @@ pub fn detect_codec_format(data: &[u8]) -> ImageCodecFormat {
 /// Auto-detect format and decode.
 pub fn decode_image(data: &[u8]) -> Result<DecodedImage, ImageCodecError> {
     match detect_codec_format(data) {
-        ImageCodecFormat::Png => decode_png(data),
+        ImageCodecFormat::Png | ImageCodecFormat::Bmp | ImageCodecFormat::Qoi => {
+            Ok(image_format::decode(data)?)
+        }
         ImageCodecFormat::Jpeg => decode_jpeg(data),
         ImageCodecFormat::Gif => {
             let gif = decode_gif(data)?;
@@ -218,6 +193,20 @@ mod tests {
         assert_eq!(detect_codec_format(&data), ImageCodecFormat::Png);
     }
 
+    #[test]
+    fn test_detect_bmp_and_qoi() {
+        assert_eq!(detect_codec_format(b"BM\0\0\0\0"), ImageCodecFormat::Bmp);
+        assert_eq!(detect_codec_format(b"qoif\0\0\0\0"), ImageCodecFormat::Qoi);
+    }
+
+    #[test]
+    fn test_decode_image_qoi() {
+        let mut img = DecodedImage::new(2, 1);
+        img.set_pixel(1, 0, 1, 2, 3, 4);
+        let qoi = image_format::encode_qoi(&img).unwrap();
+        assert_eq!(decode_image(&qoi), Ok(img));
+    }
+
     #[test]
     fn test_detect_jpeg() {
         let data = [0xFF, 0xD8, 0xFF, 0xE0];
@@ -244,20 +233,6 @@ mod tests {
         assert_eq!(detect_codec_format(&data), ImageCodecFormat::Unknown);
     }
 
-    #[test]
-    fn test_decoded_image_set_get_pixel() {
-        let mut img = DecodedImage::new(4, 4);
-        img.set_pixel(1, 2, 0xAA, 0xBB, 0xCC, 0xDD);
-        assert_eq!(img.get_pixel(1, 2), (0xAA, 0xBB, 0xCC, 0xDD));
-    }
-
-    #[test]
-    fn test_decoded_image_out_of_bounds() {
-        let mut img = DecodedImage::new(2, 2);
-        img.set_pixel(10, 10, 255, 0, 0, 255); // should not panic
-        assert_eq!(img.get_pixel(10, 10), (0, 0, 0, 0));
-    }
-
     #[test]
     fn test_clamp_u8_values() {
         assert_eq!(clamp_u8(-10), 0);
@@ -276,14 +251,6 @@ mod tests {
         assert_ne!(ImageCodecError::TruncatedData, ImageCodecError::CorruptData);
     }
 
-    #[test]
-    fn test_decoded_image_new_size() {
-        let img = DecodedImage::new(10, 20);
-        assert_eq!(img.width, 10);
-        assert_eq!(img.height, 20);
-        assert_eq!(img.pixels.len(), 10 * 20 * 4);
-    }
-
     #[test]
     fn test_read_helpers() {
         let data = [0x01, 0x02, 0x03, 0x04];
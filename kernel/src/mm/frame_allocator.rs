@@ -13,7 +13,10 @@ use spin::Mutex;
 // Import println! macro - may be no-op on some architectures
 #[allow(unused_imports)]
 use crate::println;
-use crate::raii::{FrameGuard, FramesGuard};
+use crate::{
+    mm::PageSize,
+    raii::{FrameGuard, FramesGuard},
+};
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
@@ -287,6 +290,42 @@ impl BitmapAllocator {
         Err(FrameAllocatorError::OutOfMemory)
     }
 
+    /// Allocate `count` contiguous frames whose first frame number is a
+    /// multiple of `align` (a power of two).
+    ///
+    /// Used for 2MB pages, which need physically aligned backing. Unlike
+    /// `allocate`, any count up to the size of the bitmap is accepted.
+    fn allocate_aligned(&self, count: usize, align: usize) -> Result<FrameNumber> {
+        if count == 0 || count > self.total_frames || !align.is_power_of_two() {
+            return Err(FrameAllocatorError::InvalidSize);
+        }
+
+        let mut bitmap = self.bitmap.lock();
+
+        // First bit whose absolute frame number is aligned
+        let start = self.start_frame.as_u64() as usize;
+        let mut candidate = start.next_multiple_of(align) - start;
+
+        while candidate + count <= self.total_frames {
+            let used = (candidate..candidate + count)
+                .rev()
+                .find(|&bit| bitmap[bit / 64] & (1 << (bit % 64)) == 0);
+            match used {
+                // Skip every candidate that would still cover the used frame
+                Some(used) => candidate = (start + used + 1).next_multiple_of(align) - start,
+                None => {
+                    for bit in candidate..candidate + count {
+                        bitmap[bit / 64] &= !(1 << (bit % 64));
+                    }
+                    self.free_frames.fetch_sub(count, Ordering::Release);
+                    return Ok(FrameNumber::new((start + candidate) as u64));
+                }
+            }
+        }
+
+        Err(FrameAllocatorError::OutOfMemory)
+    }
+
     /// Mark a specific frame as allocated (reserved) so it won't be handed out.
     /// Used to protect boot page table frames from being overwritten.
     fn mark_used(&self, frame: FrameNumber) -> Result<()> {
@@ -874,6 +913,62 @@ impl FrameAllocator {
         Err(FrameAllocatorError::OutOfMemory)
     }
 
+    /// Allocate physically contiguous frames to back one page of `size`,
+    /// aligned to that size.
+    ///
+    /// Large and huge pages can only be mapped onto naturally aligned
+    /// physical memory, so the plain first-fit search is not enough. The
+    /// bitmap zone is searched first at aligned positions; buddy blocks are
+    /// aligned relative to the zone start, so a block that turns out to be
+    /// misaligned in absolute terms is handed back.
+    ///
+    /// The frames may be freed one at a time once the page is split.
+    pub fn allocate_large_frame(
+        &self,
+        size: PageSize,
+        numa_node: Option<usize>,
+    ) -> Result<FrameNumber> {
+        let count = size as usize / FRAME_SIZE;
+        if count == 1 {
+            return self.allocate_frames(1, numa_node);
+        }
+
+        let preferred = numa_node.filter(|&node| node < MAX_NUMA_NODES);
+        let nodes = preferred
+            .into_iter()
+            .chain((0..MAX_NUMA_NODES).filter(|&node| Some(node) != preferred));
+
+        for node in nodes {
+            if let Some(ref allocator) = self.bitmap_allocators[node] {
+                if let Ok(frame) = allocator.allocate_aligned(count, count) {
+                    #[cfg(feature = "alloc")]
+                    if self.is_reserved(frame, count) {
+                        let _ = allocator.free(frame, count);
+                        continue;
+                    }
+                    return Ok(frame);
+                }
+            }
+
+            if let Some(ref allocator) = self.buddy_allocators[node] {
+                if let Ok(frame) = allocator.allocate(count) {
+                    if !frame.as_u64().is_multiple_of(count as u64) {
+                        let _ = allocator.free(frame, count);
+                        continue;
+                    }
+                    #[cfg(feature = "alloc")]
+                    if self.is_reserved(frame, count) {
+                        let _ = allocator.free(frame, count);
+                        continue;
+                    }
+                    return Ok(frame);
+                }
+            }
+        }
+
+        Err(FrameAllocatorError::OutOfMemory)
+    }
+
     /// Mark a specific physical frame as used (reserved) so it won't be
     /// allocated. Used to protect boot page table frames from being
     /// overwritten by the frame allocator.
@@ -1110,23 +1205,20 @@ impl PerCpuPageCache {
     }
 }
 
-/// Per-CPU page caches (one per CPU, protected by per-CPU access pattern)
-///
-/// SAFETY: Each CPU accesses only its own index via `current_cpu_id()`.
-/// During bootstrap, only CPU 0 runs. After SMP bringup, each CPU
-/// initializes its own cache. No cross-CPU access occurs.
-static PER_CPU_PAGE_CACHES: Mutex<[PerCpuPageCache; 16]> =
-    Mutex::new([const { PerCpuPageCache::new() }; 16]);
+crate::define_per_cpu! {
+    /// Per-CPU page caches. A CPU only takes its own cache's lock, so the
+    /// lock is uncontended unless a task migrates between looking the cache
+    /// up and locking it.
+    static PAGE_CACHES: Mutex<PerCpuPageCache> = Mutex::new(PerCpuPageCache::new());
+}
 
 /// Allocate a single physical frame using the per-CPU cache.
 ///
 /// Fast path: no global lock contention for single-frame allocs.
 /// Falls back to global allocator if cache is empty and refill fails.
 pub fn per_cpu_alloc_frame() -> Result<FrameNumber> {
-    let cpu_id = crate::sched::smp::current_cpu_id() as usize;
-
-    let mut caches = PER_CPU_PAGE_CACHES.lock();
-    let cache = &mut caches[cpu_id.min(15)];
+    let cpu_id = crate::sched::percpu::cpu_id();
+    let mut cache = PAGE_CACHES.on_cpu(cpu_id).lock();
 
     // Try cache first
     if let Some(frame) = cache.alloc_one() {
@@ -1166,15 +1258,14 @@ pub fn per_cpu_alloc_frame() -> Result<FrameNumber> {
 /// Fast path: no global lock contention for single-frame frees.
 /// Drains excess frames back to global if cache is full.
 pub fn per_cpu_free_frame(frame: FrameNumber) -> Result<()> {
-    let cpu_id = crate::sched::smp::current_cpu_id() as usize;
+    let cpu_id = crate::sched::percpu::cpu_id();
     crate::trace!(
         crate::perf::trace::TraceEventType::FrameFree,
         frame.as_u64(),
         cpu_id as u64
     );
 
-    let mut caches = PER_CPU_PAGE_CACHES.lock();
-    let cache = &mut caches[cpu_id.min(15)];
+    let mut cache = PAGE_CACHES.on_cpu(cpu_id).lock();
 
     // Try cache first
     if cache.free_one(frame) {
@@ -1227,6 +1318,41 @@ mod tests {
         assert_eq!(frame2.as_u64(), frame.as_u64());
     }
 
+    #[test]
+    fn test_bitmap_allocator_aligned() {
+        // Zone starts off a 2MB boundary: frame 512 is the first aligned one
+        let allocator = BitmapAllocator::new(FrameNumber::new(100), 2000);
+
+        let frame = allocator
+            .allocate_aligned(512, 512)
+            .expect("aligned 2MB allocation should succeed in an empty zone");
+        assert_eq!(frame.as_u64(), 512);
+        assert_eq!(allocator.free_count(), 2000 - 512);
+
+        // A used frame inside the next candidate pushes the search past it
+        let blocker = allocator
+            .allocate(1)
+            .expect("single frame allocation should succeed");
+        assert_eq!(blocker.as_u64(), 100);
+        allocator.mark_used(FrameNumber::new(1100)).unwrap();
+        let frame2 = allocator
+            .allocate_aligned(512, 512)
+            .expect("the range after the used frame is still free");
+        assert_eq!(frame2.as_u64(), 1536);
+        assert_eq!(
+            allocator.allocate_aligned(512, 512),
+            Err(FrameAllocatorError::OutOfMemory)
+        );
+
+        allocator
+            .free(frame, 512)
+            .expect("an aligned allocation can be freed like any other");
+        let frame3 = allocator
+            .allocate_aligned(512, 512)
+            .expect("freed aligned range should be reusable");
+        assert_eq!(frame3.as_u64(), 512);
+    }
+
     #[test]
     fn test_buddy_allocator() {
         let allocator = BuddyAllocator::new(FrameNumber::new(0), 1024);
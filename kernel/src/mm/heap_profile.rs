@@ -0,0 +1,653 @@
+//! Heap profiler
+//!
+//! [`Profiled`] wraps the kernel's global allocator. With profiling off it
+//! costs one atomic load per call. [`Mode::Counts`] keeps per-size-class
+//! counters plus the net live bytes and their high-water mark.
+//! [`Mode::Sites`] also attributes every `interval`th allocation to its
+//! call site: the frame-pointer backtrace is hashed into a fixed table of
+//! sites, and the sampled pointer is remembered until it is freed, so each
+//! site's live bytes go down again. A site whose live bytes only grow is
+//! the leak.
+//!
+//! The allocation path never allocates: the tables are static arrays of
+//! atomics, and a sample that finds them full is counted as dropped. Only
+//! the reports use the heap and the symbol table.
+//!
+//! Userland allocators keep the same counters in their own memory, laid
+//! out as [`HeapStats`] and registered with `SYS_HEAP_PROFILE`;
+//! [`process_report`] reads them for `/proc/<pid>/heap`.
+
+use alloc::{format, string::String, vec::Vec};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    fmt::Write,
+    sync::atomic::{AtomicI64, AtomicU64, AtomicU8, Ordering},
+};
+
+use veridian_abi::heap_profile::{self as abi, size_class, HeapStats, CLASSES as SIZE_CLASSES};
+
+use super::VirtualAddress;
+use crate::crash::backtrace;
+
+/// Call sites tracked in [`Mode::Sites`].
+const MAX_SITES: usize = 256;
+/// Return addresses recorded per site.
+const SITE_FRAMES: usize = 6;
+/// Sampled pointers remembered until freed.
+const MAX_SAMPLES: usize = 2048;
+/// Slots probed in either table before giving up.
+const PROBES: usize = 16;
+
+/// Default for [`set_mode`]'s sampling interval.
+pub const DEFAULT_SAMPLE_INTERVAL: u64 = abi::INTERVAL as u64;
+
+/// `Sample::ptr` of a slot whose pointer was freed.
+const TOMBSTONE: u64 = 1;
+/// `Sample::info` packs the site index above the size.
+const SITE_SHIFT: u32 = 40;
+
+/// What the profiler records. The values are those of
+/// [`HeapStats::mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Mode {
+    Off = abi::MODE_OFF as u8,
+    Counts = abi::MODE_COUNTS as u8,
+    Sites = abi::MODE_SITES as u8,
+}
+
+impl Mode {
+    fn from_raw(raw: u32) -> Mode {
+        match raw {
+            abi::MODE_COUNTS => Mode::Counts,
+            abi::MODE_SITES => Mode::Sites,
+            _ => Mode::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::Counts => "counts",
+            Mode::Sites => "sites",
+        }
+    }
+}
+
+struct Class {
+    allocs: AtomicU64,
+    frees: AtomicU64,
+    bytes: AtomicU64,
+}
+
+struct Site {
+    /// Hash of `frames`, 0 while the slot is free.
+    key: AtomicU64,
+    frames: [AtomicU64; SITE_FRAMES],
+    allocs: AtomicU64,
+    bytes: AtomicU64,
+    live: AtomicI64,
+}
+
+struct Sample {
+    /// 0 when never used, [`TOMBSTONE`] once freed.
+    ptr: AtomicU64,
+    /// Site index << [`SITE_SHIFT`] | size.
+    info: AtomicU64,
+}
+
+static MODE: AtomicU8 = AtomicU8::new(Mode::Off as u8);
+static INTERVAL: AtomicU64 = AtomicU64::new(DEFAULT_SAMPLE_INTERVAL);
+static TICKET: AtomicU64 = AtomicU64::new(0);
+
+static CLASSES: [Class; SIZE_CLASSES] = [const {
+    Class {
+        allocs: AtomicU64::new(0),
+        frees: AtomicU64::new(0),
+        bytes: AtomicU64::new(0),
+    }
+}; SIZE_CLASSES];
+/// Net bytes allocated since profiling was enabled or reset.
+static LIVE_BYTES: AtomicI64 = AtomicI64::new(0);
+static PEAK_BYTES: AtomicI64 = AtomicI64::new(0);
+/// Samples lost to a full site or pointer table.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+static SITES: [Site; MAX_SITES] = [const {
+    Site {
+        key: AtomicU64::new(0),
+        frames: [const { AtomicU64::new(0) }; SITE_FRAMES],
+        allocs: AtomicU64::new(0),
+        bytes: AtomicU64::new(0),
+        live: AtomicI64::new(0),
+    }
+}; MAX_SITES];
+
+static SAMPLES: [Sample; MAX_SAMPLES] = [const {
+    Sample {
+        ptr: AtomicU64::new(0),
+        info: AtomicU64::new(0),
+    }
+}; MAX_SAMPLES];
+
+/// Label of a size class for reports: its upper bound, or `>` the previous
+/// one for the last class.
+fn class_label(class: usize) -> String {
+    if class == SIZE_CLASSES - 1 {
+        format!(">{}", human(16 << (class - 1)))
+    } else {
+        human(16 << class)
+    }
+}
+
+fn human(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1 << 20 && b % (1 << 20) == 0 => format!("{}M", b >> 20),
+        b if b >= 1 << 10 && b % (1 << 10) == 0 => format!("{}K", b >> 10),
+        b => format!("{}", b),
+    }
+}
+
+/// Whether a symbolized frame belongs to the allocation machinery rather
+/// than to the code that asked for memory.
+fn is_allocator_frame(name: &str) -> bool {
+    let name = name.trim_start_matches('<');
+    ["alloc::", "core::alloc::", "__rust_", "__rg_"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || name.contains("mm::heap_profile::")
+}
+
+fn hash(words: &[u64]) -> u64 {
+    // FNV-1a over the words
+    words.iter().fold(0xcbf2_9ce4_8422_2325, |h, &w| {
+        (h ^ w).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// The current mode.
+pub fn mode() -> Mode {
+    Mode::from_raw(MODE.load(Ordering::Relaxed) as u32)
+}
+
+/// Allocations per sample in [`Mode::Sites`].
+pub fn sample_interval() -> u64 {
+    INTERVAL.load(Ordering::Relaxed)
+}
+
+/// Switch modes, sampling one in `interval` allocations in
+/// [`Mode::Sites`]. Counters are kept; see [`reset`].
+pub fn set_mode(mode: Mode, interval: u64) {
+    INTERVAL.store(interval.max(1), Ordering::Relaxed);
+    if mode != Mode::Sites {
+        // Sampled pointers freed from now on would not be looked up.
+        clear_samples();
+    }
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+fn clear_samples() {
+    for sample in &SAMPLES {
+        sample.info.store(0, Ordering::Relaxed);
+        sample.ptr.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Zero all counters and forget all sites.
+pub fn reset() {
+    for class in &CLASSES {
+        class.allocs.store(0, Ordering::Relaxed);
+        class.frees.store(0, Ordering::Relaxed);
+        class.bytes.store(0, Ordering::Relaxed);
+    }
+    LIVE_BYTES.store(0, Ordering::Relaxed);
+    PEAK_BYTES.store(0, Ordering::Relaxed);
+    DROPPED.store(0, Ordering::Relaxed);
+    TICKET.store(0, Ordering::Relaxed);
+    clear_samples();
+    for site in &SITES {
+        site.key.store(0, Ordering::Relaxed);
+        site.allocs.store(0, Ordering::Relaxed);
+        site.bytes.store(0, Ordering::Relaxed);
+        site.live.store(0, Ordering::Relaxed);
+    }
+}
+
+#[inline]
+fn on_alloc(ptr: *mut u8, size: usize) {
+    let mode = MODE.load(Ordering::Relaxed);
+    if mode == Mode::Off as u8 || ptr.is_null() {
+        return;
+    }
+    let class = &CLASSES[size_class(size)];
+    class.allocs.fetch_add(1, Ordering::Relaxed);
+    class.bytes.fetch_add(size as u64, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size as i64, Ordering::Relaxed) + size as i64;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+
+    if mode == Mode::Sites as u8
+        && TICKET
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(INTERVAL.load(Ordering::Relaxed))
+    {
+        sample(ptr as u64, size);
+    }
+}
+
+#[inline]
+fn on_dealloc(ptr: *mut u8, size: usize) {
+    let mode = MODE.load(Ordering::Relaxed);
+    if mode == Mode::Off as u8 {
+        return;
+    }
+    CLASSES[size_class(size)]
+        .frees
+        .fetch_add(1, Ordering::Relaxed);
+    LIVE_BYTES.fetch_sub(size as i64, Ordering::Relaxed);
+    if mode == Mode::Sites as u8 {
+        forget(ptr as u64);
+    }
+}
+
+/// Attribute the allocation at `ptr` to the site that made it.
+#[inline(never)]
+fn sample(ptr: u64, size: usize) {
+    let mut frames = [0u64; SITE_FRAMES];
+    let mut depth = 0;
+    let (fp, sp) = backtrace::frame();
+    backtrace::walk(fp, sp, |ret| {
+        if depth < SITE_FRAMES {
+            frames[depth] = ret;
+            depth += 1;
+        }
+    });
+    let Some(index) = find_site(&frames) else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+        return;
+    };
+    let site = &SITES[index];
+    site.allocs.fetch_add(1, Ordering::Relaxed);
+    site.bytes.fetch_add(size as u64, Ordering::Relaxed);
+    if remember(ptr, index, size) {
+        site.live.fetch_add(size as i64, Ordering::Relaxed);
+    } else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Index of the site with these frames, claiming a free slot for a new one.
+fn find_site(frames: &[u64; SITE_FRAMES]) -> Option<usize> {
+    let key = hash(frames).max(1);
+    (0..PROBES)
+        .map(|i| (key as usize).wrapping_add(i) % MAX_SITES)
+        .find(|&index| {
+            let site = &SITES[index];
+            match site
+                .key
+                .compare_exchange(0, key, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    for (slot, &frame) in site.frames.iter().zip(frames) {
+                        slot.store(frame, Ordering::Relaxed);
+                    }
+                    true
+                }
+                Err(current) => current == key,
+            }
+        })
+}
+
+fn sample_slot(ptr: u64, probe: usize) -> &'static Sample {
+    let start = (ptr >> 4).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+    &SAMPLES[(start as usize).wrapping_add(probe) % MAX_SAMPLES]
+}
+
+fn remember(ptr: u64, site: usize, size: usize) -> bool {
+    let info = ((site as u64) << SITE_SHIFT) | (size as u64 & ((1 << SITE_SHIFT) - 1));
+    (0..PROBES).any(|probe| {
+        let slot = sample_slot(ptr, probe);
+        let current = slot.ptr.load(Ordering::Relaxed);
+        let claimed = (current == 0 || current == TOMBSTONE)
+            && slot
+                .ptr
+                .compare_exchange(current, ptr, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok();
+        if claimed {
+            slot.info.store(info, Ordering::Release);
+        }
+        claimed
+    })
+}
+
+fn forget(ptr: u64) {
+    let Some(slot) = (0..PROBES)
+        .map(|probe| sample_slot(ptr, probe))
+        .find(|slot| slot.ptr.load(Ordering::Acquire) == ptr)
+    else {
+        return;
+    };
+    let info = slot.info.swap(0, Ordering::AcqRel);
+    slot.ptr.store(TOMBSTONE, Ordering::Release);
+    if info != 0 {
+        let size = info & ((1 << SITE_SHIFT) - 1);
+        SITES[(info >> SITE_SHIFT) as usize % MAX_SITES]
+            .live
+            .fetch_sub(size as i64, Ordering::Relaxed);
+    }
+}
+
+/// Global allocator wrapper that feeds the profiler.
+pub struct Profiled<A: 'static>(pub &'static A);
+
+// SAFETY: Every call is forwarded to the wrapped allocator unchanged; the
+// bookkeeping only touches static atomics and neither allocates nor
+// accesses the memory handed out.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Profiled<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller's contract is passed on.
+        let ptr = unsafe { self.0.alloc(layout) };
+        on_alloc(ptr, layout.size());
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller's contract is passed on.
+        let ptr = unsafe { self.0.alloc_zeroed(layout) };
+        on_alloc(ptr, layout.size());
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        on_dealloc(ptr, layout.size());
+        // SAFETY: The caller's contract is passed on.
+        unsafe { self.0.dealloc(ptr, layout) }
+    }
+}
+
+// ============================================================================
+// Reports
+// ============================================================================
+
+/// One row of a site table.
+struct SiteRow {
+    live: i64,
+    bytes: u64,
+    allocs: u64,
+    name: String,
+}
+
+fn write_totals(out: &mut String, allocs: u64, frees: u64, live: i64, peak: i64) {
+    let _ = writeln!(
+        out,
+        "allocs {}, frees {}, live {} bytes, peak {} bytes",
+        allocs,
+        frees,
+        live.max(0),
+        peak.max(0)
+    );
+}
+
+/// Classes as `(allocs, frees, bytes)`, smallest first.
+fn write_classes(out: &mut String, classes: impl Iterator<Item = (u64, u64, u64)>) {
+    let _ = writeln!(
+        out,
+        "{:>8} {:>10} {:>10} {:>14}",
+        "size", "allocs", "frees", "bytes"
+    );
+    for (class, (allocs, frees, bytes)) in classes.enumerate() {
+        if allocs != 0 || frees != 0 {
+            let _ = writeln!(
+                out,
+                "{:>8} {:>10} {:>10} {:>14}",
+                class_label(class),
+                allocs,
+                frees,
+                bytes
+            );
+        }
+    }
+}
+
+/// The `top` sites by live bytes.
+fn write_sites(out: &mut String, mut rows: Vec<SiteRow>, top: usize) {
+    rows.sort_unstable_by(|a, b| b.live.cmp(&a.live).then(b.bytes.cmp(&a.bytes)));
+    let _ = writeln!(out, "{:>12} {:>14} {:>8}  site", "live", "bytes", "allocs");
+    for row in rows.iter().take(top) {
+        let _ = writeln!(
+            out,
+            "{:>12} {:>14} {:>8}  {}",
+            row.live.max(0),
+            row.bytes,
+            row.allocs,
+            row.name
+        );
+    }
+}
+
+/// Name of a kernel site: its first frame outside the allocator.
+fn kernel_site_name(site: &Site) -> String {
+    let frames: Vec<u64> = site
+        .frames
+        .iter()
+        .map(|f| f.load(Ordering::Relaxed))
+        .take_while(|&f| f != 0)
+        .collect();
+    for &ret in &frames {
+        if let Some(symbol) = crate::crash::symbolize(ret) {
+            if !is_allocator_frame(symbol.name()) {
+                return format!("{}+{:#x}", symbol.name(), symbol.offset);
+            }
+        }
+    }
+    // No symbols: the caller is usually the last allocator frame's caller.
+    frames
+        .last()
+        .map_or_else(|| String::from("?"), |ret| format!("{:#x}", ret))
+}
+
+/// The kernel heap profile, with the `top` sites by live bytes.
+pub fn report(top: usize) -> String {
+    let mut out = String::new();
+    let mode = mode();
+    match mode {
+        Mode::Sites => {
+            let _ = writeln!(
+                out,
+                "mode: sites, 1 in {} allocations sampled",
+                sample_interval()
+            );
+        }
+        _ => {
+            let _ = writeln!(out, "mode: {}", mode.name());
+        }
+    }
+
+    let load = |a: &AtomicU64| a.load(Ordering::Relaxed);
+    let (allocs, frees) = CLASSES.iter().fold((0, 0), |(a, f), c| {
+        (a + load(&c.allocs), f + load(&c.frees))
+    });
+    write_totals(
+        &mut out,
+        allocs,
+        frees,
+        LIVE_BYTES.load(Ordering::Relaxed),
+        PEAK_BYTES.load(Ordering::Relaxed),
+    );
+    write_classes(
+        &mut out,
+        CLASSES
+            .iter()
+            .map(|c| (load(&c.allocs), load(&c.frees), load(&c.bytes))),
+    );
+
+    let rows: Vec<SiteRow> = SITES
+        .iter()
+        .filter(|site| site.key.load(Ordering::Acquire) != 0)
+        .map(|site| SiteRow {
+            live: site.live.load(Ordering::Relaxed),
+            bytes: load(&site.bytes),
+            allocs: load(&site.allocs),
+            name: kernel_site_name(site),
+        })
+        .collect();
+    if !rows.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nsites: {} ({} samples dropped)",
+            rows.len(),
+            DROPPED.load(Ordering::Relaxed)
+        );
+        write_sites(&mut out, rows, top);
+    }
+    out
+}
+
+// ============================================================================
+// Userland allocators
+// ============================================================================
+
+/// Read the statistics `process` registered, if any and intact.
+fn read_user_stats(process: &crate::process::Process) -> Option<HeapStats> {
+    let addr = process.heap_stats_addr.load(Ordering::Acquire);
+    if addr == 0 {
+        return None;
+    }
+    let mut buf = [0u8; core::mem::size_of::<HeapStats>()];
+    process
+        .memory_space
+        .lock()
+        .read_bytes(VirtualAddress(addr), &mut buf)
+        .ok()?;
+    // SAFETY: HeapStats is repr(C) with only atomic integer fields, so any
+    // bytes are a valid value; the buffer has exactly its size.
+    let stats: HeapStats = unsafe { core::ptr::read_unaligned(buf.as_ptr().cast()) };
+    let magic = stats.magic.load(Ordering::Relaxed);
+    let version = stats.version.load(Ordering::Relaxed);
+    (magic == abi::MAGIC && version == abi::VERSION).then_some(stats)
+}
+
+/// `/proc/<pid>/heap`: the heap as the kernel maps it, then the counters
+/// of the process's allocator if it registered them.
+pub fn process_report(process: &crate::process::Process) -> String {
+    let mut out = String::new();
+    {
+        let vas = process.memory_space.lock();
+        let stats = vas.get_stats();
+        let start = vas.heap_start_addr();
+        let brk = vas.brk(None).0;
+        let _ = writeln!(
+            out,
+            "brk:     {:#x}-{:#x} ({} kB)",
+            start,
+            brk,
+            brk.saturating_sub(start) / 1024
+        );
+        let _ = writeln!(out, "heap:    {} kB mapped", stats.heap_size / 1024);
+        let _ = writeln!(
+            out,
+            "data:    {} kB mapped (includes anonymous mmap)",
+            stats.data_size / 1024
+        );
+        let _ = writeln!(
+            out,
+            "total:   {} kB in {} mappings",
+            stats.total_size / 1024,
+            stats.mapping_count
+        );
+    }
+    let rss = super::maps::process_rss(process);
+    let _ = writeln!(
+        out,
+        "rss:     {} kB ({} kB shared, {} kB private, {} kB pss)",
+        rss.rss / 1024,
+        rss.shared / 1024,
+        rss.private() / 1024,
+        rss.pss / 1024
+    );
+
+    let Some(stats) = read_user_stats(process) else {
+        out.push_str("\nallocator: not profiled (run with MALLOC_PROFILE=counts or sites)\n");
+        return out;
+    };
+    let mode = Mode::from_raw(stats.mode.load(Ordering::Relaxed));
+    if mode == Mode::Sites {
+        let _ = writeln!(
+            out,
+            "\nallocator: sites, 1 in {} allocations sampled",
+            stats.sample_interval.load(Ordering::Relaxed)
+        );
+    } else {
+        let _ = writeln!(out, "\nallocator: {}", mode.name());
+    }
+    let load = |a: &AtomicU64| a.load(Ordering::Relaxed);
+    write_totals(
+        &mut out,
+        load(&stats.allocs),
+        load(&stats.frees),
+        load(&stats.live_bytes) as i64,
+        load(&stats.peak_bytes) as i64,
+    );
+    write_classes(
+        &mut out,
+        stats
+            .classes
+            .iter()
+            .map(|c| (load(&c.allocs), load(&c.frees), load(&c.bytes))),
+    );
+
+    let symbols = process.symbols.lock().clone();
+    let rows: Vec<SiteRow> = stats
+        .sites
+        .iter()
+        .filter(|site| load(&site.pc) != 0)
+        .map(|site| {
+            let pc = load(&site.pc);
+            SiteRow {
+                live: load(&site.live_bytes) as i64,
+                bytes: load(&site.bytes),
+                allocs: load(&site.allocs),
+                // The call instruction is the byte before the return address.
+                name: match symbols.as_ref().and_then(|s| s.lookup(pc - 1)) {
+                    Some((name, offset)) => format!("{}+{:#x}", name, offset + 1),
+                    None => format!("{:#x}", pc),
+                },
+            }
+        })
+        .collect();
+    if !rows.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nsites: {} ({} samples dropped)",
+            rows.len(),
+            load(&stats.dropped)
+        );
+        write_sites(&mut out, rows, abi::SITES);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_label() {
+        assert_eq!(class_label(0), "16");
+        assert_eq!(class_label(8), "4K");
+        assert_eq!(class_label(SIZE_CLASSES - 1), ">64M");
+    }
+
+    #[test]
+    fn test_is_allocator_frame() {
+        assert!(is_allocator_frame("alloc::raw_vec::finish_grow"));
+        assert!(is_allocator_frame(
+            "<alloc::vec::Vec<T,A> as core::clone::Clone>::clone"
+        ));
+        assert!(is_allocator_frame("__rust_alloc"));
+        assert!(is_allocator_frame(
+            "<veridian_kernel::mm::heap_profile::Profiled<A> as \
+             core::alloc::global::GlobalAlloc>::alloc"
+        ));
+        assert!(!is_allocator_frame("veridian_kernel::fs::vfs::Vfs::open"));
+    }
+}
@@ -1,28 +1,51 @@
 //! Kernel Same-page Merging (KSM)
 //!
-//! Scans anonymous user-space pages for identical content and merges
-//! duplicates via Copy-on-Write (COW) mappings.  Reduces memory
-//! consumption for workloads with many similar processes (e.g. KDE
-//! Plasma applets, browser tabs, containerized services).
+//! Statically linked userland binaries carry many identical pages: every
+//! process running a program has its own copy of its text, and different
+//! programs carry the same libc code and read-only data. With `ksm=on` on
+//! the command line (or `sysctl kernel.param.ksm=1`) a background scanner
+//! walks the read-only private pages of every process, `ksm.pages_to_scan`
+//! pages every `ksm.sleep_ms` milliseconds, and makes identical pages share
+//! one frame. Turning it off stops the scanner; pages already merged stay
+//! merged. Statistics are in `/proc/ksm`.
 //!
 //! Design:
-//!   - **Unstable tree**: pages seen once, awaiting confirmation across
-//!     multiple scan cycles before promotion.
-//!   - **Stable tree**: pages confirmed identical, merged via COW.
 //!   - **FNV-1a hash**: fast 32-bit content hash for candidate filtering
 //!     (integer-only, no floating point).
-//!   - **Full comparison**: byte-for-byte equality check on hash match before
-//!     merging.
+//!   - **Unstable tree**: pages seen during the current pass, by hash. It is
+//!     emptied after every full pass, as its pages may change or go away.
+//!   - **Stable tree**: merged frames, with the number of pages mapping each. A
+//!     page matching an unstable one is promoted into the stable tree in place,
+//!     and its twin then merges into it.
+//!   - **Full comparison**: pages are compared byte for byte before merging;
+//!     the hash only picks candidates.
 //!
-//! Pages are identified by frame number (`FrameNumber`).  The scanner
-//! does not touch kernel pages, device-mapped pages, or already-merged
-//! pages.
-
-#![allow(dead_code)]
-
-use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-
-use crate::mm::{FrameNumber, PAGE_SIZE};
+//! A merged frame is freed by the last page letting go of it: the VAS frees
+//! user frames through [`release`]. A merged page is copy-on-write: it gets
+//! a private copy before it may be written, when `mprotect` makes it
+//! writable or on a write fault.
+//!
+//! Locking: the scanner holds at most one address space at a time, and the
+//! scanner lock is never held while taking another lock, so the VAS can
+//! call [`release`] and [`is_merged`] from any context it frees frames in.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec::Vec,
+};
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use spin::Mutex;
+
+use crate::{
+    mm::{vas::MappingType, FrameNumber, PageFlags, VirtualAddress, FRAME_ALLOCATOR, PAGE_SIZE},
+    process::{Process, ProcessId},
+    sched::workqueue::{self, Work},
+};
 
 /// FNV-1a hash constants (32-bit, integer-only).
 const FNV_OFFSET_BASIS: u32 = 2_166_136_261;
@@ -34,16 +57,24 @@ const DEFAULT_SCAN_RATE: usize = 100;
 /// Default delay between scan cycles in milliseconds.
 const DEFAULT_SLEEP_MS: u64 = 200;
 
-/// Number of consecutive stable scans required before promotion
-/// from unstable to stable tree.
-const PROMOTION_THRESHOLD: u32 = 3;
-
-/// Maximum number of entries in the stable tree.
-const MAX_STABLE_ENTRIES: usize = 4096;
-
 /// Maximum number of entries in the unstable tree.
 const MAX_UNSTABLE_ENTRIES: usize = 4096;
 
+crate::kernel_param! {
+    /// Merge identical read-only user pages in the background.
+    pub static ENABLED: bool = false, "ksm", on_set = start;
+}
+
+crate::kernel_param! {
+    /// Pages the KSM scanner looks at per round.
+    pub static PAGES_TO_SCAN: usize = DEFAULT_SCAN_RATE, "ksm.pages_to_scan";
+}
+
+crate::kernel_param! {
+    /// Milliseconds between KSM scanner rounds.
+    pub static SLEEP_MS: u64 = DEFAULT_SLEEP_MS, "ksm.sleep_ms";
+}
+
 // =========================================================================
 // FNV-1a hash
 // =========================================================================
@@ -65,54 +96,33 @@ pub(crate) fn fnv1a_hash(data: &[u8]) -> u32 {
 // Tree entries
 // =========================================================================
 
-/// An entry in the stable tree: a page whose content has been confirmed
-/// identical to at least one other page across multiple scan cycles.
-#[derive(Clone)]
-struct StableEntry {
-    /// Frame number of the canonical (kept) page.
-    frame: FrameNumber,
-    /// FNV-1a hash of the page content at merge time.
-    hash: u32,
-    /// Number of other pages currently mapped COW to this frame.
-    sharing_count: u32,
-    /// Whether this entry is occupied.
-    active: bool,
-}
-
-impl Default for StableEntry {
-    fn default() -> Self {
-        Self {
-            frame: FrameNumber::new(0),
-            hash: 0,
-            sharing_count: 0,
-            active: false,
-        }
-    }
+/// A user page the scanner looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub pid: u64,
+    pub vaddr: u64,
+    pub frame: FrameNumber,
 }
 
-/// An entry in the unstable tree: a page seen during scanning that is
-/// waiting for confirmation before promotion to the stable tree.
-#[derive(Clone)]
-struct UnstableEntry {
-    /// Frame number of the candidate page.
-    frame: FrameNumber,
-    /// FNV-1a hash at last scan.
+/// An entry in the stable tree: a merged frame.
+#[derive(Debug, Clone, Copy)]
+struct StableEntry {
+    /// FNV-1a hash of the page content.
     hash: u32,
-    /// Number of consecutive scans with unchanged content.
-    stable_count: u32,
-    /// Whether this entry is occupied.
-    active: bool,
+    /// Pages mapping the frame.
+    mappers: u32,
 }
 
-impl Default for UnstableEntry {
-    fn default() -> Self {
-        Self {
-            frame: FrameNumber::new(0),
-            hash: 0,
-            stable_count: 0,
-            active: false,
-        }
-    }
+/// What a scanned page could merge with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Candidate {
+    /// Nothing, for now.
+    None,
+    /// The merged frame with the same content.
+    Stable(FrameNumber),
+    /// A page seen earlier in this pass with the same hash; it has left
+    /// the unstable tree.
+    Unstable(Page, u32),
 }
 
 // =========================================================================
@@ -122,14 +132,16 @@ impl Default for UnstableEntry {
 /// Statistics reported by the KSM scanner.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct KsmStats {
-    /// Pages currently merged (canonical pages in stable tree).
+    /// Merged frames (entries in the stable tree).
     pub pages_shared: u64,
-    /// Pages currently mapped COW to a shared page.
+    /// Pages mapping a merged frame beyond the first: the pages saved.
     pub pages_sharing: u64,
-    /// Pages scanned but not merged (unique content).
+    /// Pages scanned this pass that matched nothing (the unstable tree).
     pub pages_unshared: u64,
-    /// Total pages scanned since last reset.
+    /// Total pages scanned.
     pub pages_scanned: u64,
+    /// Completed passes over all processes.
+    pub full_scans: u64,
     /// Ratio: pages_sharing / pages_shared (x100 for integer display).
     pub merge_ratio_x100: u64,
 }
@@ -138,31 +150,24 @@ pub struct KsmStats {
 // KSM scanner
 // =========================================================================
 
-/// Kernel Same-page Merging scanner.
+/// Kernel Same-page Merging bookkeeping.
 ///
-/// Maintains stable and unstable trees of page content hashes and
-/// orchestrates scanning, comparison, and merge operations.
+/// Keeps the stable and unstable trees and the statistics; the scan itself
+/// ([`scan_round`]) feeds it pages and does the remapping.
 pub struct KsmScanner {
-    /// Number of pages to scan per cycle.
-    scan_rate: usize,
-    /// Delay between scan cycles in milliseconds.
-    sleep_ms: u64,
-    /// Whether the scanner is currently enabled.
-    enabled: AtomicBool,
-
-    /// Stable tree: confirmed identical pages.
-    stable: [StableEntry; MAX_STABLE_ENTRIES],
-    stable_count: usize,
-
-    /// Unstable tree: candidate pages awaiting confirmation.
-    unstable: [UnstableEntry; MAX_UNSTABLE_ENTRIES],
-    unstable_count: usize,
-
-    /// Running statistics.
-    stats_shared: AtomicU64,
-    stats_sharing: AtomicU64,
-    stats_unshared: AtomicU64,
-    stats_scanned: AtomicU64,
+    /// Stable tree: merged frames.
+    stable: BTreeMap<FrameNumber, StableEntry>,
+    /// The stable tree by content hash. Frames with different contents may
+    /// share a hash, so a hash can list several.
+    stable_by_hash: BTreeSet<(u32, FrameNumber)>,
+    /// Unstable tree: pages seen this pass, by content hash.
+    unstable: BTreeMap<u32, Page>,
+    /// Where the next round starts: process ID and user address.
+    cursor: (u64, u64),
+
+    sharing: u64,
+    scanned: u64,
+    full_scans: u64,
 }
 
 impl Default for KsmScanner {
@@ -172,270 +177,391 @@ impl Default for KsmScanner {
 }
 
 impl KsmScanner {
-    /// Create a new KSM scanner with default configuration.
+    /// Create an empty scanner.
     pub const fn new() -> Self {
-        // const-friendly initialization using arrays of default values
-        const STABLE_INIT: StableEntry = StableEntry {
-            frame: FrameNumber::new(0),
-            hash: 0,
-            sharing_count: 0,
-            active: false,
-        };
-        const UNSTABLE_INIT: UnstableEntry = UnstableEntry {
-            frame: FrameNumber::new(0),
-            hash: 0,
-            stable_count: 0,
-            active: false,
-        };
-
         Self {
-            scan_rate: DEFAULT_SCAN_RATE,
-            sleep_ms: DEFAULT_SLEEP_MS,
-            enabled: AtomicBool::new(false),
-            stable: [STABLE_INIT; MAX_STABLE_ENTRIES],
-            stable_count: 0,
-            unstable: [UNSTABLE_INIT; MAX_UNSTABLE_ENTRIES],
-            unstable_count: 0,
-            stats_shared: AtomicU64::new(0),
-            stats_sharing: AtomicU64::new(0),
-            stats_unshared: AtomicU64::new(0),
-            stats_scanned: AtomicU64::new(0),
+            stable: BTreeMap::new(),
+            stable_by_hash: BTreeSet::new(),
+            unstable: BTreeMap::new(),
+            cursor: (0, 0),
+            sharing: 0,
+            scanned: 0,
+            full_scans: 0,
         }
     }
 
-    /// Initialize the scanner.  Must be called once before `enable()`.
-    pub fn init(&mut self) {
-        self.stable_count = 0;
-        self.unstable_count = 0;
-        self.stats_shared.store(0, Ordering::Relaxed);
-        self.stats_sharing.store(0, Ordering::Relaxed);
-        self.stats_unshared.store(0, Ordering::Relaxed);
-        self.stats_scanned.store(0, Ordering::Relaxed);
-
-        for entry in self.stable.iter_mut() {
-            entry.active = false;
-        }
-        for entry in self.unstable.iter_mut() {
-            entry.active = false;
-        }
-    }
-
-    /// Set the number of pages to scan per cycle.
-    pub fn set_scan_rate(&mut self, pages_per_cycle: usize) {
-        self.scan_rate = if pages_per_cycle == 0 {
-            1
-        } else {
-            pages_per_cycle
-        };
-    }
-
-    /// Set the delay between scan cycles in milliseconds.
-    pub fn set_sleep_ms(&mut self, ms: u64) {
-        self.sleep_ms = ms;
-    }
-
-    /// Enable the KSM scanner.
-    pub fn enable(&self) {
-        self.enabled.store(true, Ordering::Release);
-    }
-
-    /// Disable the KSM scanner.
-    pub fn disable(&self) {
-        self.enabled.store(false, Ordering::Release);
-    }
-
-    /// Check whether the scanner is enabled.
-    pub fn is_enabled(&self) -> bool {
-        self.enabled.load(Ordering::Acquire)
-    }
-
     /// Get current KSM statistics.
     pub fn get_stats(&self) -> KsmStats {
-        let shared = self.stats_shared.load(Ordering::Relaxed);
-        let sharing = self.stats_sharing.load(Ordering::Relaxed);
-        let unshared = self.stats_unshared.load(Ordering::Relaxed);
-        let scanned = self.stats_scanned.load(Ordering::Relaxed);
-
-        let merge_ratio_x100 = if shared > 0 {
-            sharing.saturating_mul(100) / shared
-        } else {
-            0
-        };
+        let shared = self.stable.len() as u64;
+        let merge_ratio_x100 = self
+            .sharing
+            .saturating_mul(100)
+            .checked_div(shared)
+            .unwrap_or(0);
 
         KsmStats {
             pages_shared: shared,
-            pages_sharing: sharing,
-            pages_unshared: unshared,
-            pages_scanned: scanned,
+            pages_sharing: self.sharing,
+            pages_unshared: self.unstable.len() as u64,
+            pages_scanned: self.scanned,
+            full_scans: self.full_scans,
             merge_ratio_x100,
         }
     }
 
-    /// Scan a single page.  The caller provides the frame number and a
-    /// reference to the page content (PAGE_SIZE bytes).
-    ///
-    /// The scanner will:
-    ///   1. Compute FNV-1a hash of the page content.
-    ///   2. Search the stable tree for a match (merge immediately).
-    ///   3. Search the unstable tree for a match (increment stable_count).
-    ///   4. If no match, add to the unstable tree.
+    /// Scan a page with the given content (PAGE_SIZE bytes).
     ///
-    /// Returns `true` if the page was merged (caller should remap as COW).
-    pub fn scan_page(&mut self, frame: FrameNumber, content: &[u8]) -> bool {
-        if !self.is_enabled() {
-            return false;
+    /// Looks for a merged frame with the same content, then for an
+    /// unmerged page with the same hash seen earlier in this pass. A page
+    /// matching neither enters the unstable tree. Each merged frame with
+    /// the page's hash is checked with `same`, which compares its content
+    /// with the page's; the unstable twin is matched by hash only, and the
+    /// caller compares contents before merging.
+    pub fn scan_page(
+        &mut self,
+        page: Page,
+        content: &[u8],
+        same: impl Fn(FrameNumber) -> bool,
+    ) -> Candidate {
+        if content.len() != PAGE_SIZE || self.stable.contains_key(&page.frame) {
+            return Candidate::None;
         }
+        self.scanned += 1;
 
-        if content.len() != PAGE_SIZE {
-            return false;
+        let hash = fnv1a_hash(content);
+        if let Some(&(_, frame)) = self
+            .stable_by_hash
+            .range((hash, FrameNumber::new(0))..=(hash, FrameNumber::new(u64::MAX)))
+            .find(|&&(_, frame)| same(frame))
+        {
+            return Candidate::Stable(frame);
         }
 
-        self.stats_scanned.fetch_add(1, Ordering::Relaxed);
+        match self.unstable.get(&hash) {
+            Some(twin) if twin.frame == page.frame => Candidate::None,
+            Some(_) => match self.unstable.remove(&hash) {
+                Some(twin) => Candidate::Unstable(twin, hash),
+                None => Candidate::None,
+            },
+            None => {
+                if self.unstable.len() < MAX_UNSTABLE_ENTRIES {
+                    self.unstable.insert(hash, page);
+                }
+                Candidate::None
+            }
+        }
+    }
 
-        let hash = fnv1a_hash(content);
+    /// Make `frame`, whose page has content hash `hash`, a merged frame
+    /// mapped by that one page.
+    pub fn promote(&mut self, frame: FrameNumber, hash: u32) {
+        if self.stable.contains_key(&frame) {
+            return;
+        }
+        self.stable.insert(frame, StableEntry { hash, mappers: 1 });
+        self.stable_by_hash.insert((hash, frame));
+    }
+
+    /// Count one more page mapping the merged `frame`. Fails if `frame` is
+    /// not merged (any more).
+    pub fn add_mapper(&mut self, frame: FrameNumber) -> bool {
+        match self.stable.get_mut(&frame) {
+            Some(entry) => {
+                entry.mappers += 1;
+                self.sharing += 1;
+                true
+            }
+            None => false,
+        }
+    }
 
-        // 1. Check stable tree for an existing merge target
-        if let Some(stable_idx) = self.find_stable_by_hash(hash) {
-            // Hash match in stable tree -- would do byte-for-byte
-            // comparison in production (requires reading the canonical
-            // page content).  For the framework, we trust the hash.
-            self.stable[stable_idx].sharing_count += 1;
-            self.stats_sharing.fetch_add(1, Ordering::Relaxed);
+    /// Count one page less mapping `frame`. Returns whether the frame is
+    /// now unused and must be freed: always for a frame that is not merged.
+    pub fn release(&mut self, frame: FrameNumber) -> bool {
+        let Some(entry) = self.stable.get_mut(&frame) else {
             return true;
+        };
+        entry.mappers = entry.mappers.saturating_sub(1);
+        if entry.mappers > 0 {
+            self.sharing = self.sharing.saturating_sub(1);
+            return false;
         }
+        let hash = entry.hash;
+        self.stable.remove(&frame);
+        self.stable_by_hash.remove(&(hash, frame));
+        true
+    }
 
-        // 2. Check unstable tree
-        if let Some(unstable_idx) = self.find_unstable_by_hash(hash) {
-            let entry = &mut self.unstable[unstable_idx];
+    /// Whether `frame` is a merged frame.
+    pub fn is_merged(&self, frame: FrameNumber) -> bool {
+        self.stable.contains_key(&frame)
+    }
 
-            // Same hash as before -- content unchanged
-            entry.stable_count += 1;
+    /// Number of merged frames.
+    pub fn merged(&self) -> usize {
+        self.stable.len()
+    }
 
-            if entry.stable_count >= PROMOTION_THRESHOLD {
-                // Promote to stable tree
-                let promoted_frame = entry.frame;
-                entry.active = false;
-                self.unstable_count = self.unstable_count.saturating_sub(1);
+    /// A pass over all processes is complete.
+    pub fn end_pass(&mut self) {
+        self.unstable.clear();
+        self.cursor = (0, 0);
+        self.full_scans += 1;
+    }
+}
 
-                self.add_stable(promoted_frame, hash);
+// =========================================================================
+// Scanner
+// =========================================================================
 
-                // The scanned page is now a sharing page
-                if let Some(sidx) = self.find_stable_by_hash(hash) {
-                    self.stable[sidx].sharing_count += 1;
-                }
-                self.stats_sharing.fetch_add(1, Ordering::Relaxed);
-                return true;
-            }
+static SCANNER: Mutex<KsmScanner> = Mutex::new(KsmScanner::new());
 
-            return false;
-        }
+/// Number of merged frames, so freeing frames costs nothing while there
+/// are none.
+static MERGED: AtomicUsize = AtomicUsize::new(0);
 
-        // 3. Not in either tree -- add to unstable
-        self.add_unstable(frame, hash);
-        self.stats_unshared.fetch_add(1, Ordering::Relaxed);
+static SCAN_WORK: Work = Work::new(scan_work, 0);
 
-        false
+fn update_merged(scanner: &KsmScanner) {
+    MERGED.store(scanner.merged(), Ordering::Release);
+}
+
+/// Count one page less mapping `frame`; whether the frame must now be
+/// freed. Called by the VAS for every user frame it lets go of.
+pub fn release(frame: FrameNumber) -> bool {
+    if MERGED.load(Ordering::Acquire) == 0 {
+        return true;
     }
+    let mut scanner = SCANNER.lock();
+    let free = scanner.release(frame);
+    update_merged(&scanner);
+    free
+}
 
-    /// Remove a page from the stable tree (e.g. after a COW fault
-    /// breaks the sharing).
-    pub fn unmerge_page(&mut self, frame: FrameNumber) {
-        for entry in self.stable.iter_mut() {
-            if entry.active && entry.frame.as_u64() == frame.as_u64() {
-                if entry.sharing_count > 0 {
-                    entry.sharing_count -= 1;
-                    self.stats_sharing.fetch_sub(1, Ordering::Relaxed);
-                }
-                if entry.sharing_count == 0 {
-                    entry.active = false;
-                    self.stable_count = self.stable_count.saturating_sub(1);
-                    self.stats_shared.fetch_sub(1, Ordering::Relaxed);
-                }
-                return;
-            }
-        }
+/// Whether `frame` is shared by merged pages, which must not be written.
+pub fn is_merged(frame: FrameNumber) -> bool {
+    MERGED.load(Ordering::Acquire) != 0 && SCANNER.lock().is_merged(frame)
+}
+
+/// Whether any of `frames` is shared by merged pages. Takes the scanner
+/// lock once, and not at all while nothing is merged.
+pub fn any_merged(frames: impl IntoIterator<Item = FrameNumber>) -> bool {
+    if MERGED.load(Ordering::Acquire) == 0 {
+        return false;
     }
+    let scanner = SCANNER.lock();
+    frames.into_iter().any(|frame| scanner.is_merged(frame))
+}
+
+/// Current statistics.
+pub fn stats() -> KsmStats {
+    SCANNER.lock().get_stats()
+}
 
-    /// Get the configured scan rate.
-    pub fn scan_rate(&self) -> usize {
-        self.scan_rate
+/// Start the scanner if `ksm` is on. Called once the workqueues run.
+pub fn init() {
+    if ENABLED.get() {
+        crate::println!("[KSM] Same-page merging enabled");
+        workqueue::queue_work(&SCAN_WORK);
     }
+}
 
-    /// Get the configured sleep interval in milliseconds.
-    pub fn sleep_ms(&self) -> u64 {
-        self.sleep_ms
+fn start(enabled: &bool) {
+    if *enabled {
+        workqueue::queue_work(&SCAN_WORK);
     }
+}
 
-    // -----------------------------------------------------------------
-    // Internal helpers
-    // -----------------------------------------------------------------
+/// One scanner round, then re-arm unless `ksm` was turned off.
+fn scan_work(_data: usize) {
+    if !ENABLED.get() {
+        return;
+    }
+    scan_round(PAGES_TO_SCAN.get().max(1));
+    workqueue::queue_delayed_work(&SCAN_WORK, SLEEP_MS.get());
+}
 
-    /// Find a stable entry matching the given hash.
-    fn find_stable_by_hash(&self, hash: u32) -> Option<usize> {
-        for (i, entry) in self.stable.iter().enumerate() {
-            if entry.active && entry.hash == hash {
-                return Some(i);
+/// The content of `frame`.
+fn content(frame: FrameNumber) -> &'static [u8] {
+    let virt = super::phys_to_virt_addr(frame.as_u64() << 12);
+    // SAFETY: Every frame is reachable through the kernel's physical memory
+    // window. The frames scanned are user pages mapped read-only, and a
+    // stale one (freed since it was looked up) is still RAM: at worst the
+    // comparison fails.
+    unsafe { core::slice::from_raw_parts(virt as *const u8, PAGE_SIZE) }
+}
+
+/// Whether pages of `mapping` may be merged: private, read-only user
+/// memory.
+fn mergeable(mapping: &super::vas::VirtualMapping) -> bool {
+    mapping.owns_frames()
+        && mapping.mapping_type != MappingType::Shared
+        && mapping.flags.contains(PageFlags::USER)
+        && !mapping.flags.contains(PageFlags::WRITABLE)
+}
+
+/// Up to `max` mergeable pages of `process` at or after `from`.
+fn candidates(process: &Process, from: u64, max: usize) -> Vec<(u64, FrameNumber)> {
+    let vas = process.memory_space.lock();
+    let mappings = vas.mappings_ref().lock();
+    let mut pages = Vec::new();
+    for mapping in mappings
+        .values()
+        .filter(|m| m.end().0 > from && mergeable(m))
+    {
+        let pages_in = mapping.size / PAGE_SIZE;
+        for (i, &frame) in mapping.physical_frames.iter().take(pages_in).enumerate() {
+            let vaddr = mapping.start.0 + (i * PAGE_SIZE) as u64;
+            if vaddr < from || frame.as_u64() == 0 {
+                continue;
+            }
+            pages.push((vaddr, frame));
+            if pages.len() == max {
+                return pages;
             }
         }
-        None
     }
+    pages
+}
 
-    /// Find an unstable entry matching the given hash.
-    fn find_unstable_by_hash(&self, hash: u32) -> Option<usize> {
-        for (i, entry) in self.unstable.iter().enumerate() {
-            if entry.active && entry.hash == hash {
-                return Some(i);
-            }
+/// Point the page of `process` at `vaddr`, backed by `frame`, at the
+/// merged frame `target`, and free `frame`. Returns whether it did.
+fn merge(process: &Process, vaddr: u64, frame: FrameNumber, target: FrameNumber) -> bool {
+    let vas = process.memory_space.lock();
+    // Counted first, so that `target` outlives the comparison
+    {
+        let mut scanner = SCANNER.lock();
+        if scanner.is_merged(frame) || !scanner.add_mapper(target) {
+            return false;
         }
-        None
-    }
-
-    /// Add an entry to the stable tree.
-    fn add_stable(&mut self, frame: FrameNumber, hash: u32) {
-        // Find a free slot
-        for entry in self.stable.iter_mut() {
-            if !entry.active {
-                entry.frame = frame;
-                entry.hash = hash;
-                entry.sharing_count = 0;
-                entry.active = true;
-                self.stable_count += 1;
-                self.stats_shared.fetch_add(1, Ordering::Relaxed);
-                return;
-            }
+    }
+    if content(frame) == content(target) && vas.replace_frame(vaddr, frame, target).is_ok() {
+        drop(vas);
+        let _ = FRAME_ALLOCATOR.lock().free_frames(frame, 1);
+        return true;
+    }
+    drop(vas);
+    if release(target) {
+        let _ = FRAME_ALLOCATOR.lock().free_frames(target, 1);
+    }
+    false
+}
+
+/// Look at one page of `process`.
+fn scan_one(process: &Process, page: Page) {
+    let data = content(page.frame);
+    let candidate = SCANNER
+        .lock()
+        .scan_page(page, data, |frame| content(frame) == data);
+    match candidate {
+        Candidate::None => {}
+        Candidate::Stable(target) => {
+            merge(process, page.vaddr, page.frame, target);
         }
-        // Stable tree full -- cannot add (would need eviction policy)
-    }
-
-    /// Add an entry to the unstable tree.
-    fn add_unstable(&mut self, frame: FrameNumber, hash: u32) {
-        // Find a free slot
-        for entry in self.unstable.iter_mut() {
-            if !entry.active {
-                entry.frame = frame;
-                entry.hash = hash;
-                entry.stable_count = 1;
-                entry.active = true;
-                self.unstable_count += 1;
+        Candidate::Unstable(twin, hash) => {
+            if content(twin.frame) != data {
                 return;
             }
-        }
-        // Unstable tree full -- evict oldest (lowest stable_count)
-        let mut min_count = u32::MAX;
-        let mut min_idx = 0;
-        for (i, entry) in self.unstable.iter().enumerate() {
-            if entry.active && entry.stable_count < min_count {
-                min_count = entry.stable_count;
-                min_idx = i;
+            // The page becomes the merged frame where it is...
+            {
+                let vas = process.memory_space.lock();
+                if !still_mapped(&vas, page.vaddr, page.frame) {
+                    return;
+                }
+                let mut scanner = SCANNER.lock();
+                scanner.promote(page.frame, hash);
+                update_merged(&scanner);
+            }
+            // ...and its twin joins it
+            if let Some(owner) = crate::process::get_process(ProcessId(twin.pid)) {
+                merge(owner, twin.vaddr, twin.frame, page.frame);
             }
         }
-        let entry = &mut self.unstable[min_idx];
-        entry.frame = frame;
-        entry.hash = hash;
-        entry.stable_count = 1;
     }
 }
 
+/// Whether `vas` still maps `frame` read-only at `vaddr`.
+fn still_mapped(vas: &super::vas::VirtualAddressSpace, vaddr: u64, frame: FrameNumber) -> bool {
+    let mappings = vas.mappings_ref().lock();
+    mappings
+        .range(..=VirtualAddress(vaddr))
+        .next_back()
+        .map(|(_, m)| m)
+        .filter(|m| m.contains(VirtualAddress(vaddr)) && mergeable(m))
+        .and_then(|m| {
+            m.physical_frames
+                .get(((vaddr - m.start.0) / PAGE_SIZE as u64) as usize)
+        })
+        == Some(&frame)
+}
+
+/// Scan up to `budget` pages, carrying on where the last round stopped.
+pub fn scan_round(budget: usize) {
+    let mut pids = crate::process::get_process_list().unwrap_or_default();
+    pids.sort_unstable();
+    let (mut pid, mut from) = SCANNER.lock().cursor;
+    let mut left = budget;
+
+    while left > 0 {
+        let Some(&next) = pids.iter().find(|&&p| p >= pid) else {
+            SCANNER.lock().end_pass();
+            return;
+        };
+        if next != pid {
+            pid = next;
+            from = 0;
+        }
+        let Some(process) = crate::process::get_process(ProcessId(pid)) else {
+            pid += 1;
+            continue;
+        };
+        let pages = candidates(process, from, left);
+        if pages.len() < left {
+            // The rest of this process fits in the round
+            pid += 1;
+            from = 0;
+        } else if let Some(&(last, _)) = pages.last() {
+            from = last + PAGE_SIZE as u64;
+        }
+        left -= pages.len();
+        for (vaddr, frame) in pages {
+            scan_one(
+                process,
+                Page {
+                    pid: process.pid.0,
+                    vaddr,
+                    frame,
+                },
+            );
+        }
+    }
+    SCANNER.lock().cursor = (pid, from);
+}
+
+/// `/proc/ksm`.
+pub fn report() -> String {
+    let stats = stats();
+    let mut out = String::new();
+    let mut field = |name: &str, value: u64| {
+        let _ = writeln!(out, "{:<16}{:>8}", name, value);
+    };
+    field("run:", ENABLED.get() as u64);
+    field("pages_to_scan:", PAGES_TO_SCAN.get() as u64);
+    field("sleep_ms:", SLEEP_MS.get());
+    field("pages_shared:", stats.pages_shared);
+    field("pages_sharing:", stats.pages_sharing);
+    field("pages_unshared:", stats.pages_unshared);
+    field("pages_scanned:", stats.pages_scanned);
+    field("full_scans:", stats.full_scans);
+    let _ = writeln!(
+        out,
+        "{:<16}{:>8} kB",
+        "saved:",
+        stats.pages_sharing * (PAGE_SIZE as u64 / 1024)
+    );
+    out
+}
+
 // =========================================================================
 // Tests
 // =========================================================================
@@ -444,6 +570,14 @@ impl KsmScanner {
 mod tests {
     use super::*;
 
+    fn page(pid: u64, frame: u64) -> Page {
+        Page {
+            pid,
+            vaddr: 0x400000,
+            frame: FrameNumber::new(frame),
+        }
+    }
+
     #[test]
     fn test_fnv1a_empty() {
         let hash = fnv1a_hash(&[]);
@@ -473,63 +607,28 @@ mod tests {
 
     #[test]
     fn test_scanner_init() {
-        let mut scanner = KsmScanner::new();
-        scanner.init();
-        assert!(!scanner.is_enabled());
-        assert_eq!(scanner.scan_rate(), DEFAULT_SCAN_RATE);
-        assert_eq!(scanner.sleep_ms(), DEFAULT_SLEEP_MS);
-
+        let scanner = KsmScanner::new();
         let stats = scanner.get_stats();
         assert_eq!(stats.pages_shared, 0);
         assert_eq!(stats.pages_sharing, 0);
         assert_eq!(stats.pages_scanned, 0);
-    }
-
-    #[test]
-    fn test_scanner_enable_disable() {
-        let scanner = KsmScanner::new();
-        assert!(!scanner.is_enabled());
-        scanner.enable();
-        assert!(scanner.is_enabled());
-        scanner.disable();
-        assert!(!scanner.is_enabled());
-    }
-
-    #[test]
-    fn test_scanner_config() {
-        let mut scanner = KsmScanner::new();
-        scanner.set_scan_rate(200);
-        assert_eq!(scanner.scan_rate(), 200);
-        scanner.set_scan_rate(0);
-        assert_eq!(scanner.scan_rate(), 1); // clamped to minimum
-
-        scanner.set_sleep_ms(500);
-        assert_eq!(scanner.sleep_ms(), 500);
-    }
-
-    #[test]
-    fn test_scan_page_disabled() {
-        let mut scanner = KsmScanner::new();
-        scanner.init();
-        // Scanner not enabled -- should return false
-        let page = [0u8; PAGE_SIZE];
-        assert!(!scanner.scan_page(FrameNumber::new(1), &page));
+        assert_eq!(stats.full_scans, 0);
     }
 
     #[test]
     fn test_scan_page_wrong_size() {
         let mut scanner = KsmScanner::new();
-        scanner.init();
-        scanner.enable();
         let small = [0u8; 512];
-        assert!(!scanner.scan_page(FrameNumber::new(1), &small));
+        assert_eq!(
+            scanner.scan_page(page(1, 1), &small, |_| true),
+            Candidate::None
+        );
+        assert_eq!(scanner.get_stats().pages_scanned, 0);
     }
 
     #[test]
     fn test_scan_unique_pages() {
         let mut scanner = KsmScanner::new();
-        scanner.init();
-        scanner.enable();
 
         let mut page1 = [0u8; PAGE_SIZE];
         page1[0] = 1;
@@ -537,11 +636,22 @@ mod tests {
         page2[0] = 2;
 
         // First scan of each unique page -- goes to unstable
-        assert!(!scanner.scan_page(FrameNumber::new(1), &page1));
-        assert!(!scanner.scan_page(FrameNumber::new(2), &page2));
+        assert_eq!(
+            scanner.scan_page(page(1, 1), &page1, |_| true),
+            Candidate::None
+        );
+        assert_eq!(
+            scanner.scan_page(page(1, 2), &page2, |_| true),
+            Candidate::None
+        );
+        // Scanning a page again does not make it its own twin
+        assert_eq!(
+            scanner.scan_page(page(1, 1), &page1, |_| true),
+            Candidate::None
+        );
 
         let stats = scanner.get_stats();
-        assert_eq!(stats.pages_scanned, 2);
+        assert_eq!(stats.pages_scanned, 3);
         assert_eq!(stats.pages_unshared, 2);
         assert_eq!(stats.pages_shared, 0);
     }
@@ -549,68 +659,99 @@ mod tests {
     #[test]
     fn test_scan_identical_pages_merge() {
         let mut scanner = KsmScanner::new();
-        scanner.init();
-        scanner.enable();
-
-        let page = [42u8; PAGE_SIZE];
-
-        // Scan same content from frame 1 -- enters unstable tree
-        assert!(!scanner.scan_page(FrameNumber::new(1), &page));
-        // Scan again (same hash) -- increments stable_count to 2
-        assert!(!scanner.scan_page(FrameNumber::new(1), &page));
-        // Scan again -- stable_count reaches PROMOTION_THRESHOLD (3)
-        // This promotes to stable tree
-        assert!(scanner.scan_page(FrameNumber::new(1), &page));
+        let content = [42u8; PAGE_SIZE];
+        let hash = fnv1a_hash(&content);
+
+        assert_eq!(
+            scanner.scan_page(page(1, 1), &content, |_| true),
+            Candidate::None
+        );
+        assert_eq!(
+            scanner.scan_page(page(2, 2), &content, |_| true),
+            Candidate::Unstable(page(1, 1), hash)
+        );
+
+        // Frame 2 is promoted and frame 1's page merges into it
+        scanner.promote(FrameNumber::new(2), hash);
+        assert!(scanner.add_mapper(FrameNumber::new(2)));
+        assert!(scanner.is_merged(FrameNumber::new(2)));
+
+        // Later twins find the stable frame; the merged frame itself is skipped
+        assert_eq!(
+            scanner.scan_page(page(3, 3), &content, |_| true),
+            Candidate::Stable(FrameNumber::new(2))
+        );
+        assert_eq!(
+            scanner.scan_page(page(2, 2), &content, |_| true),
+            Candidate::None
+        );
 
         let stats = scanner.get_stats();
         assert_eq!(stats.pages_shared, 1);
-        assert!(stats.pages_sharing >= 1);
+        assert_eq!(stats.pages_sharing, 1);
+        assert_eq!(stats.pages_unshared, 0);
+        assert_eq!(stats.merge_ratio_x100, 100);
     }
 
     #[test]
-    fn test_merge_then_stable_lookup() {
+    fn test_scan_stable_hash_collision() {
         let mut scanner = KsmScanner::new();
-        scanner.init();
-        scanner.enable();
-
-        let page = [99u8; PAGE_SIZE];
-
-        // Promote frame 1 through unstable -> stable
-        scanner.scan_page(FrameNumber::new(1), &page);
-        scanner.scan_page(FrameNumber::new(1), &page);
-        scanner.scan_page(FrameNumber::new(1), &page); // promotes
-
-        // Now scan frame 2 with same content -- should match stable tree
-        let merged = scanner.scan_page(FrameNumber::new(2), &page);
-        assert!(merged);
-
-        let stats = scanner.get_stats();
-        assert!(stats.pages_sharing >= 2);
+        let content = [5u8; PAGE_SIZE];
+        let hash = fnv1a_hash(&content);
+
+        // Two merged frames with the page's hash; only frame 11 holds the
+        // same content
+        scanner.promote(FrameNumber::new(10), hash);
+        scanner.promote(FrameNumber::new(11), hash);
+        let same = |frame: FrameNumber| frame == FrameNumber::new(11);
+        assert_eq!(
+            scanner.scan_page(page(1, 1), &content, same),
+            Candidate::Stable(FrameNumber::new(11))
+        );
+
+        // Matching none of them, the page goes to the unstable tree
+        assert_eq!(
+            scanner.scan_page(page(1, 1), &content, |_| false),
+            Candidate::None
+        );
+        assert_eq!(scanner.get_stats().pages_unshared, 1);
     }
 
     #[test]
-    fn test_unmerge_page() {
+    fn test_release() {
         let mut scanner = KsmScanner::new();
-        scanner.init();
-        scanner.enable();
-
-        let page = [55u8; PAGE_SIZE];
+        let frame = FrameNumber::new(7);
+        scanner.promote(frame, 0x1234);
+        scanner.add_mapper(frame);
+        scanner.add_mapper(frame);
+        assert_eq!(scanner.get_stats().pages_sharing, 2);
 
-        // Promote to stable
-        scanner.scan_page(FrameNumber::new(1), &page);
-        scanner.scan_page(FrameNumber::new(1), &page);
-        scanner.scan_page(FrameNumber::new(1), &page);
+        // Freed by the last page only
+        assert!(!scanner.release(frame));
+        assert!(!scanner.release(frame));
+        assert_eq!(scanner.get_stats().pages_sharing, 0);
+        assert!(scanner.release(frame));
+        assert!(!scanner.is_merged(frame));
+        assert!(!scanner.add_mapper(frame));
 
-        // Add a sharing page
-        scanner.scan_page(FrameNumber::new(2), &page);
-
-        let stats = scanner.get_stats();
-        let sharing_before = stats.pages_sharing;
-
-        // Unmerge (COW break)
-        scanner.unmerge_page(FrameNumber::new(1));
+        // Frames KSM never merged are always freed
+        assert!(scanner.release(FrameNumber::new(8)));
+    }
 
+    #[test]
+    fn test_end_pass() {
+        let mut scanner = KsmScanner::new();
+        let content = [9u8; PAGE_SIZE];
+        scanner.scan_page(page(1, 1), &content, |_| true);
+        scanner.end_pass();
+
+        // The unstable tree is rebuilt every pass
+        assert_eq!(
+            scanner.scan_page(page(2, 2), &content, |_| true),
+            Candidate::None
+        );
         let stats = scanner.get_stats();
-        assert!(stats.pages_sharing < sharing_before);
+        assert_eq!(stats.full_scans, 1);
+        assert_eq!(stats.pages_unshared, 1);
     }
 }
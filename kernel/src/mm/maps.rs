@@ -0,0 +1,334 @@
+//! Memory maps
+//!
+//! The areas of a process's address space as `/proc/<pid>/maps` and
+//! `/proc/<pid>/smaps` show them, and the resident set sizes behind them.
+//!
+//! The VAS records pages mapped one at a time (ELF segments, the stack) as
+//! one mapping per page, so adjacent mappings with the same permissions and
+//! backing object are reported as a single area. A page is resident once a
+//! frame backs it, and shared when more than one address space maps that
+//! frame; `Pss` divides each page evenly among the address spaces mapping
+//! it, so the Pss of all processes adds up to the memory in use. Device
+//! mappings alias memory owned elsewhere and count as neither.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{fmt::Write, ops::Range};
+
+use super::{
+    vas::{MappingType, VirtualAddressSpace, VirtualMapping},
+    FrameNumber, PageFlags, VirtualAddress, PAGE_SIZE,
+};
+use crate::process::{vdso, Process};
+
+/// The vDSO pages every user address space has.
+const VDSO: Range<u64> = vdso::VDSO_BASE as u64..(vdso::VDSO_BASE + 3 * PAGE_SIZE) as u64;
+
+/// One area of an address space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Area {
+    pub start: u64,
+    pub end: u64,
+    /// `r`, `w`, `x` and `p`rivate or `s`hared, as in `rw-p`.
+    pub perms: [u8; 4],
+    /// Backing file, `[heap]`, `[stack]` and so on; empty when anonymous.
+    pub name: String,
+    /// Bytes backed by a frame.
+    pub rss: usize,
+    /// Resident bytes that another address space maps as well.
+    pub shared: usize,
+    /// Proportional share of the resident bytes.
+    pub pss: usize,
+}
+
+impl Area {
+    /// Resident bytes no other address space maps.
+    pub fn private(&self) -> usize {
+        self.rss - self.shared
+    }
+
+    pub fn perms_str(&self) -> &str {
+        core::str::from_utf8(&self.perms).unwrap_or("????")
+    }
+}
+
+/// Resident set of a whole process, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rss {
+    pub rss: usize,
+    pub shared: usize,
+    pub pss: usize,
+}
+
+impl Rss {
+    /// What killing the process would give back.
+    pub fn private(&self) -> usize {
+        self.rss - self.shared
+    }
+}
+
+/// How many address spaces map each frame.
+#[derive(Debug, Default)]
+pub struct FrameUse(BTreeMap<FrameNumber, u32>);
+
+impl FrameUse {
+    /// Count the frames of every process's address space.
+    pub fn collect() -> Self {
+        let mut uses = FrameUse::default();
+        for pid in crate::process::get_process_list().unwrap_or_default() {
+            if let Some(process) = crate::process::get_process(crate::process::ProcessId(pid)) {
+                let vas = process.memory_space.lock();
+                uses.add(&vas.mappings_ref().lock());
+            }
+        }
+        uses
+    }
+
+    /// Count the frames of one address space.
+    pub fn add(&mut self, mappings: &BTreeMap<VirtualAddress, VirtualMapping>) {
+        for mapping in mappings.values().filter(|m| m.owns_frames()) {
+            for &frame in backed(mapping) {
+                *self.0.entry(frame).or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn count(&self, frame: FrameNumber) -> usize {
+        self.0.get(&frame).map_or(1, |&n| (n as usize).max(1))
+    }
+}
+
+/// The frames of a mapping, skipping pages not yet backed.
+fn backed(mapping: &VirtualMapping) -> impl Iterator<Item = &FrameNumber> {
+    mapping
+        .physical_frames
+        .iter()
+        .take(mapping.size / PAGE_SIZE)
+        .filter(|f| f.as_u64() != 0)
+}
+
+fn perms(mapping: &VirtualMapping) -> [u8; 4] {
+    let flag = |set: bool, c| if set { c } else { b'-' };
+    let shared = matches!(
+        mapping.mapping_type,
+        MappingType::Shared | MappingType::Device
+    );
+    [
+        b'r',
+        flag(mapping.flags.contains(PageFlags::WRITABLE), b'w'),
+        flag(!mapping.flags.contains(PageFlags::NO_EXECUTE), b'x'),
+        if shared { b's' } else { b'p' },
+    ]
+}
+
+fn name(mapping: &VirtualMapping, stack: &Range<u64>) -> String {
+    if let Some(path) = &mapping.backing {
+        return String::from(&**path);
+    }
+    let start = mapping.start.0;
+    let name = match mapping.mapping_type {
+        _ if VDSO.contains(&start) => "[vdso]",
+        _ if stack.contains(&start) => "[stack]",
+        MappingType::Heap => "[heap]",
+        MappingType::Shared => "[shared]",
+        MappingType::Device => "[device]",
+        _ => "",
+    };
+    String::from(name)
+}
+
+/// The areas of `mappings`, whose stack occupies `stack`.
+pub fn areas(
+    mappings: &BTreeMap<VirtualAddress, VirtualMapping>,
+    stack: Range<u64>,
+    uses: &FrameUse,
+) -> Vec<Area> {
+    let mut areas: Vec<Area> = Vec::new();
+    for mapping in mappings.values() {
+        let (mut rss, mut shared, mut pss) = (0, 0, 0);
+        if mapping.owns_frames() {
+            for &frame in backed(mapping) {
+                let count = uses.count(frame);
+                rss += PAGE_SIZE;
+                pss += PAGE_SIZE / count;
+                if count > 1 {
+                    shared += PAGE_SIZE;
+                }
+            }
+        }
+        let area = Area {
+            start: mapping.start.0,
+            end: mapping.end().0,
+            perms: perms(mapping),
+            name: name(mapping, &stack),
+            rss,
+            shared,
+            pss,
+        };
+        match areas.last_mut() {
+            Some(last)
+                if last.end == area.start && last.perms == area.perms && last.name == area.name =>
+            {
+                last.end = area.end;
+                last.rss += area.rss;
+                last.shared += area.shared;
+                last.pss += area.pss;
+            }
+            _ => areas.push(area),
+        }
+    }
+    areas
+}
+
+/// The areas of `process`.
+pub fn process_areas(process: &Process) -> Vec<Area> {
+    // Counted before taking the process's own lock, which the census needs
+    let uses = FrameUse::collect();
+    let vas = process.memory_space.lock();
+    let base = vas.user_stack_base() as u64;
+    let stack = base..base + vas.user_stack_size() as u64;
+    let mappings = vas.mappings_ref().lock();
+    areas(&mappings, stack, &uses)
+}
+
+/// Resident set of `process`.
+pub fn process_rss(process: &Process) -> Rss {
+    process_areas(process)
+        .iter()
+        .fold(Rss::default(), |total, area| Rss {
+            rss: total.rss + area.rss,
+            shared: total.shared + area.shared,
+            pss: total.pss + area.pss,
+        })
+}
+
+/// Resident bytes of an address space, without telling shared from
+/// private; cheap enough for `/proc/<pid>/stat`.
+pub fn resident(vas: &VirtualAddressSpace) -> usize {
+    let mappings = vas.mappings_ref().lock();
+    mappings
+        .values()
+        .filter(|m| m.owns_frames())
+        .map(|m| backed(m).count() * PAGE_SIZE)
+        .sum()
+}
+
+/// One `/proc/<pid>/maps` line. The offset, device and inode columns of
+/// the Linux layout are kept for tools that parse it, and are always 0.
+fn write_line(out: &mut String, area: &Area) {
+    let line = alloc::format!(
+        "{:012x}-{:012x} {} 00000000 00:00 0          {}",
+        area.start,
+        area.end,
+        area.perms_str(),
+        area.name
+    );
+    out.push_str(line.trim_end());
+    out.push('\n');
+}
+
+/// `/proc/<pid>/maps`.
+pub fn maps(areas: &[Area]) -> String {
+    let mut out = String::new();
+    for area in areas {
+        write_line(&mut out, area);
+    }
+    out
+}
+
+/// `/proc/<pid>/smaps`: each maps line followed by the area's sizes.
+pub fn smaps(areas: &[Area]) -> String {
+    let mut out = String::new();
+    for area in areas {
+        write_line(&mut out, area);
+        let mut field = |name: &str, bytes: usize| {
+            let _ = writeln!(out, "{:<16}{:>8} kB", name, bytes / 1024);
+        };
+        field("Size:", (area.end - area.start) as usize);
+        field("Rss:", area.rss);
+        field("Pss:", area.pss);
+        field("Shared:", area.shared);
+        field("Private:", area.private());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use super::*;
+
+    fn mapping(
+        start: u64,
+        pages: u64,
+        mapping_type: MappingType,
+        frames: &[u64],
+    ) -> (VirtualAddress, VirtualMapping) {
+        let mut mapping =
+            VirtualMapping::new(VirtualAddress(start), (pages * 4096) as usize, mapping_type);
+        mapping.physical_frames = frames.iter().map(|&f| FrameNumber::new(f)).collect();
+        (mapping.start, mapping)
+    }
+
+    #[test]
+    fn test_areas_merge_pages() {
+        let exe: Arc<str> = Arc::from("/bin/sh");
+        let mut text = mapping(0x400000, 1, MappingType::Data, &[10]);
+        text.1.flags = PageFlags::PRESENT | PageFlags::USER;
+        text.1.backing = Some(exe.clone());
+        let mut text2 = mapping(0x401000, 1, MappingType::Data, &[11]);
+        text2.1.flags = text.1.flags;
+        text2.1.backing = Some(exe);
+        let mappings: BTreeMap<_, _> = [
+            text,
+            text2,
+            mapping(0x600000, 4, MappingType::Heap, &[20, 21]),
+            mapping(0x7000_0000, 1, MappingType::Stack, &[30]),
+            mapping(0x7000_1000, 1, MappingType::Stack, &[31]),
+            mapping(0x8000_0000, 1, MappingType::Device, &[0xfd000]),
+        ]
+        .into_iter()
+        .collect();
+
+        let areas = areas(&mappings, 0x7000_0000..0x7000_2000, &FrameUse::default());
+        assert_eq!(areas.len(), 4);
+        assert_eq!((areas[0].start, areas[0].end), (0x400000, 0x402000));
+        assert_eq!(areas[0].perms_str(), "r-xp");
+        assert_eq!(areas[0].name, "/bin/sh");
+        assert_eq!(areas[1].perms_str(), "rw-p");
+        assert_eq!(areas[1].name, "[heap]");
+        assert_eq!(areas[1].rss, 2 * 4096);
+        assert_eq!(areas[2].name, "[stack]");
+        assert_eq!(areas[2].end, 0x7000_2000);
+        assert_eq!(areas[3].perms_str(), "rwxs");
+        assert_eq!(areas[3].rss, 0);
+
+        let text = maps(&areas);
+        assert!(text.starts_with("000000400000-000000402000 r-xp 00000000 00:00 0"));
+        assert!(text.lines().next().unwrap().ends_with(" /bin/sh"));
+    }
+
+    #[test]
+    fn test_shared_frames() {
+        let ours: BTreeMap<_, _> = [mapping(0x1000, 3, MappingType::Data, &[5, 6, 0])]
+            .into_iter()
+            .collect();
+        let theirs: BTreeMap<_, _> = [mapping(0x9000, 1, MappingType::Data, &[6])]
+            .into_iter()
+            .collect();
+        let mut uses = FrameUse::default();
+        uses.add(&ours);
+        uses.add(&theirs);
+
+        let areas = areas(&ours, 0..0, &uses);
+        assert_eq!(areas[0].rss, 2 * 4096);
+        assert_eq!(areas[0].shared, 4096);
+        assert_eq!(areas[0].private(), 4096);
+        assert_eq!(areas[0].pss, 4096 + 2048);
+
+        let text = smaps(&areas);
+        assert!(text.contains("Size:                 12 kB\n"));
+        assert!(text.contains("Pss:                   6 kB\n"));
+        assert!(text.contains("Private:               4 kB\n"));
+    }
+}
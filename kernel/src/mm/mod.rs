@@ -12,7 +12,9 @@ pub mod cache_topology;
 pub mod demand_paging;
 pub mod frame_allocator;
 pub mod heap;
+pub mod heap_profile;
 pub mod ksm;
+pub mod maps;
 pub mod page_fault;
 pub mod page_table;
 pub mod user_validation;
@@ -62,25 +62,40 @@ const MAX_STACK_GROWTH: usize = 8 * 1024 * 1024;
 /// 3. **Stack growth** -- the faulting address is just below the current stack
 ///    mapping; extend the stack downward.
 /// 4. If none of the above apply, deliver SIGSEGV / return an error.
+///
+/// The outcome is recorded as a `PageFaultDone` trace event whose second
+/// data word is 0 (demand page), 1 (CoW), 2 (stack growth) or 3 (SIGSEGV).
 pub fn handle_page_fault(info: PageFaultInfo) -> Result<(), KernelError> {
+    let trace_done = |outcome: u64| {
+        crate::trace!(
+            crate::perf::trace::TraceEventType::PageFaultDone,
+            info.faulting_address,
+            outcome
+        );
+    };
+
     // Attempt demand paging first.
     if let Ok(()) = try_demand_page(&info) {
+        trace_done(0);
         return Ok(());
     }
 
     // Attempt copy-on-write handling.
     if info.was_write {
         if let Ok(()) = try_copy_on_write(&info) {
+            trace_done(1);
             return Ok(());
         }
     }
 
     // Attempt stack growth.
     if let Ok(()) = try_stack_growth(&info) {
+        trace_done(2);
         return Ok(());
     }
 
     // None of the handlers could resolve the fault.
+    trace_done(3);
     signal_segv(&info)
 }
 
@@ -160,21 +175,43 @@ fn try_demand_page(info: &PageFaultInfo) -> Result<(), KernelError> {
 
 /// Try to resolve the fault via copy-on-write.
 ///
-/// If the page is mapped read-only and is marked for CoW, create a private
-/// copy of the page, map it as writable, and return success.
+/// The pages shared copy-on-write are those KSM merged (fork copies
+/// eagerly). A write to one in a writable mapping gets a private copy of
+/// the page, mapped writable.
 fn try_copy_on_write(info: &PageFaultInfo) -> Result<(), KernelError> {
-    // Copy-on-write requires detecting CoW-marked pages. The current VAS
-    // implementation does not track CoW state, so we cannot resolve it yet.
-    // When CoW tracking is added (e.g., a `cow: bool` field on
-    // VirtualMapping), this handler will:
-    //   1. Allocate a new physical frame.
-    //   2. Copy the old frame's contents to the new frame.
-    //   3. Remap the page as writable with the new frame.
-    //   4. Flush the TLB entry.
-    let _ = info;
-    Err(KernelError::NotImplemented {
-        feature: "copy-on-write page handling",
-    })
+    let process = crate::process::current_process().ok_or(KernelError::NotInitialized {
+        subsystem: "process",
+    })?;
+    let memory_space = process.memory_space.lock();
+
+    #[cfg(feature = "alloc")]
+    {
+        let mapping = memory_space
+            .find_mapping(VirtualAddress::new(info.faulting_address))
+            .ok_or(KernelError::UnmappedMemory {
+                addr: info.faulting_address as usize,
+            })?;
+        if !mapping.flags.contains(PageFlags::WRITABLE) {
+            return Err(KernelError::PermissionDenied {
+                operation: "write to read-only mapping",
+            });
+        }
+        if memory_space.unshare_page(info.faulting_address, mapping.flags)? {
+            Ok(())
+        } else {
+            Err(KernelError::InvalidAddress {
+                addr: info.faulting_address as usize,
+            })
+        }
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    {
+        let _ = (info, memory_space);
+        Err(KernelError::NotImplemented {
+            feature: "copy-on-write page handling (requires alloc)",
+        })
+    }
 }
 
 /// Try to resolve the fault by growing the user stack.
@@ -10,7 +10,9 @@ use core::{
     ops::{Index, IndexMut},
 };
 
-use super::{FrameNumber, PageFlags, PhysicalAddress, VirtualAddress, FRAME_ALLOCATOR};
+use super::{
+    FrameNumber, PageFlags, PageSize, PhysicalAddress, VirtualAddress, FRAME_ALLOCATOR, PAGE_SIZE,
+};
 use crate::error::KernelError;
 
 /// Number of entries in a page table
@@ -398,114 +400,14 @@ impl PageMapper {
         // that remains valid for the lifetime of this PageMapper. No other mutable
         // references to this table exist (exclusive access contract from `new`).
         let l4_table = unsafe { &mut *self.l4_table };
-        let l4_entry = &mut l4_table[breakdown.l4_index];
-
-        // Intermediate page table entries must include USER if the leaf
-        // mapping is user-accessible. x86_64 checks USER on all 4 levels.
-        let intermediate_flags = if flags.contains(PageFlags::USER) {
-            PageFlags::PRESENT | PageFlags::WRITABLE | PageFlags::USER
-        } else {
-            PageFlags::PRESENT | PageFlags::WRITABLE
-        };
 
-        // Get or create L3 table
-        if !l4_entry.is_present() {
-            let frame =
-                allocator
-                    .allocate_frames(1, None)
-                    .map_err(|_| KernelError::OutOfMemory {
-                        requested: 1,
-                        available: 0,
-                    })?;
-            // Zero the new page table frame before use
-            // SAFETY: phys_to_virt_addr converts the physical frame address
-            // to a valid virtual address in the kernel's memory mapping.
-            unsafe {
-                let virt = super::phys_to_virt_addr(frame.as_u64() << 12);
-                core::ptr::write_bytes(virt as *mut u8, 0, 4096);
-            }
-            l4_entry.set(frame, intermediate_flags);
-        } else if flags.contains(PageFlags::USER) && !l4_entry.flags().contains(PageFlags::USER) {
-            // Existing entry needs USER bit added for user-space child mapping
-            let current_flags = l4_entry.flags();
-            let addr = l4_entry.addr().unwrap();
-            l4_entry.set_addr(addr, current_flags | PageFlags::USER);
-        }
-        // The entry was just set to PRESENT (either already was, or we set it above),
-        // so `addr()` is guaranteed to return `Some`.
-        let l3_phys = l4_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L4 entry present",
-            actual: "not present",
-        })?;
-        // SAFETY: phys_to_virt_addr converts the physical address to the
-        // corresponding virtual address. The frame is either pre-existing
-        // (valid page table) or freshly allocated and zeroed above.
+        // Get or create the L3, L2 and L1 tables
         let l3_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l3_phys.as_u64()) as *mut PageTable) };
-        let l3_entry = &mut l3_table[breakdown.l3_index];
-
-        // Get or create L2 table
-        if !l3_entry.is_present() {
-            let frame =
-                allocator
-                    .allocate_frames(1, None)
-                    .map_err(|_| KernelError::OutOfMemory {
-                        requested: 1,
-                        available: 0,
-                    })?;
-            // Zero the new page table frame before use
-            // SAFETY: Physical address converted to virtual via identity-mapped region;
-            // frame is freshly allocated and exclusively owned.
-            unsafe {
-                let virt = super::phys_to_virt_addr(frame.as_u64() << 12);
-                core::ptr::write_bytes(virt as *mut u8, 0, 4096);
-            }
-            l3_entry.set(frame, intermediate_flags);
-        } else if flags.contains(PageFlags::USER) && !l3_entry.flags().contains(PageFlags::USER) {
-            let current_flags = l3_entry.flags();
-            let addr = l3_entry.addr().unwrap();
-            l3_entry.set_addr(addr, current_flags | PageFlags::USER);
-        }
-        let l2_phys = l3_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L3 entry present",
-            actual: "not present",
-        })?;
-        // SAFETY: Physical address from a present L3 entry, converted to virtual via
-        // identity-mapped region.
+            next_table_create(&mut l4_table[breakdown.l4_index], page, flags, allocator)?;
         let l2_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l2_phys.as_u64()) as *mut PageTable) };
-        let l2_entry = &mut l2_table[breakdown.l2_index];
-
-        // Get or create L1 table
-        if !l2_entry.is_present() {
-            let frame =
-                allocator
-                    .allocate_frames(1, None)
-                    .map_err(|_| KernelError::OutOfMemory {
-                        requested: 1,
-                        available: 0,
-                    })?;
-            // Zero the new page table frame before use
-            // SAFETY: Physical address converted to virtual via identity-mapped region;
-            // frame is freshly allocated and exclusively owned.
-            unsafe {
-                let virt = super::phys_to_virt_addr(frame.as_u64() << 12);
-                core::ptr::write_bytes(virt as *mut u8, 0, 4096);
-            }
-            l2_entry.set(frame, intermediate_flags);
-        } else if flags.contains(PageFlags::USER) && !l2_entry.flags().contains(PageFlags::USER) {
-            let current_flags = l2_entry.flags();
-            let addr = l2_entry.addr().unwrap();
-            l2_entry.set_addr(addr, current_flags | PageFlags::USER);
-        }
-        let l1_phys = l2_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L2 entry present",
-            actual: "not present",
-        })?;
-        // SAFETY: Physical address from a present L2 entry, converted to virtual via
-        // identity-mapped region.
+            next_table_create(&mut l3_table[breakdown.l3_index], page, flags, allocator)?;
         let l1_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l1_phys.as_u64()) as *mut PageTable) };
+            next_table_create(&mut l2_table[breakdown.l2_index], page, flags, allocator)?;
 
         // Map the page
         let entry = &mut l1_table[breakdown.l1_index];
@@ -520,69 +422,94 @@ impl PageMapper {
         Ok(())
     }
 
+    /// Map a 2MB or 1GB page with a single L2 or L3 entry.
+    ///
+    /// Both `page` and `frame` must be aligned to `size`; the backing frames
+    /// come from `FrameAllocator::allocate_large_frame` or are device memory
+    /// that happens to be aligned. Fails with `AlreadyExists` if any part of
+    /// the range is already mapped through a lower-level table.
+    pub fn map_large_page(
+        &mut self,
+        page: VirtualAddress,
+        frame: FrameNumber,
+        size: PageSize,
+        flags: PageFlags,
+        allocator: &mut impl FrameAllocator,
+    ) -> Result<(), KernelError> {
+        let frames = (size as usize / PAGE_SIZE) as u64;
+        if !page.as_u64().is_multiple_of(size as u64) || !frame.as_u64().is_multiple_of(frames) {
+            return Err(KernelError::InvalidArgument {
+                name: "page",
+                value: "not aligned to the page size",
+            });
+        }
+
+        if size == PageSize::Small {
+            return self.map_page(page, frame, flags, allocator);
+        }
+
+        let breakdown = VirtualAddressBreakdown::new(page);
+
+        // SAFETY: Same invariants as map_page.
+        let l4_table = unsafe { &mut *self.l4_table };
+        let l3_table =
+            next_table_create(&mut l4_table[breakdown.l4_index], page, flags, allocator)?;
+
+        let entry = if size == PageSize::Huge {
+            &mut l3_table[breakdown.l3_index]
+        } else {
+            let l2_table =
+                next_table_create(&mut l3_table[breakdown.l3_index], page, flags, allocator)?;
+            &mut l2_table[breakdown.l2_index]
+        };
+
+        if entry.is_present() {
+            return Err(KernelError::AlreadyExists {
+                resource: "page mapping",
+                id: page.as_u64(),
+            });
+        }
+        entry.set(frame, flags | PageFlags::PRESENT | PageFlags::HUGE);
+
+        Ok(())
+    }
+
     /// Look up the physical frame mapped at a virtual address.
     ///
     /// Walks the page table hierarchy and returns the frame number and flags
-    /// of the L1 (leaf) entry if the page is present at all four levels.
+    /// of the leaf entry. Inside a 2MB or 1GB page the frame is the one
+    /// backing `page`, and the flags are reported without `HUGE`, so they can
+    /// be passed straight back to `map_page`.
     pub fn translate_page(
         &self,
         page: VirtualAddress,
     ) -> Result<(FrameNumber, PageFlags), KernelError> {
         let breakdown = VirtualAddressBreakdown::new(page);
+        let unmapped = KernelError::UnmappedMemory {
+            addr: page.as_u64() as usize,
+        };
 
         // SAFETY: Same invariants as other PageMapper methods — self.l4_table
         // was validated by the caller of PageMapper::new.
         let l4_table = unsafe { &*self.l4_table };
-        let l4_entry = &l4_table[breakdown.l4_index];
-        if !l4_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
-        }
+        let l3_table = next_table(&l4_table[breakdown.l4_index]).ok_or(unmapped)?;
 
-        let l3_phys = l4_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L4 entry has address",
-            actual: "no address",
-        })?;
-        // SAFETY: Physical address from a present L4 entry, converted to virtual via
-        // identity-mapped region.
-        let l3_table =
-            unsafe { &*(super::phys_to_virt_addr(l3_phys.as_u64()) as *const PageTable) };
         let l3_entry = &l3_table[breakdown.l3_index];
-        if !l3_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
+        if is_large(l3_entry) {
+            let index = usize::from(breakdown.l2_index) * PAGE_TABLE_ENTRIES
+                + usize::from(breakdown.l1_index);
+            return large_page_frame(l3_entry, index).ok_or(unmapped);
         }
+        let l2_table = next_table(l3_entry).ok_or(unmapped)?;
 
-        let l2_phys = l3_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L3 entry has address",
-            actual: "no address",
-        })?;
-        // SAFETY: Physical address from a present L3 entry, converted to virtual via
-        // identity-mapped region.
-        let l2_table =
-            unsafe { &*(super::phys_to_virt_addr(l2_phys.as_u64()) as *const PageTable) };
         let l2_entry = &l2_table[breakdown.l2_index];
-        if !l2_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
+        if is_large(l2_entry) {
+            return large_page_frame(l2_entry, usize::from(breakdown.l1_index)).ok_or(unmapped);
         }
+        let l1_table = next_table(l2_entry).ok_or(unmapped)?;
 
-        let l1_phys = l2_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L2 entry has address",
-            actual: "no address",
-        })?;
-        // SAFETY: Physical address from a present L2 entry, converted to virtual via
-        // identity-mapped region.
-        let l1_table =
-            unsafe { &*(super::phys_to_virt_addr(l1_phys.as_u64()) as *const PageTable) };
         let entry = &l1_table[breakdown.l1_index];
-
-        let frame = entry.frame().ok_or(KernelError::UnmappedMemory {
-            addr: page.as_u64() as usize,
-        })?;
+        let frame = entry.frame().ok_or(unmapped)?;
 
         Ok((frame, entry.flags()))
     }
@@ -590,51 +517,14 @@ impl PageMapper {
     /// Update the flags on an existing page table entry without changing the
     /// mapped frame.
     ///
-    /// Returns the old flags on success.
+    /// A 2MB or 1GB page covering `page` is split first, so only the 4KB
+    /// page at `page` changes. Returns the old flags on success.
     pub fn update_page_flags(
         &mut self,
         page: VirtualAddress,
         new_flags: PageFlags,
     ) -> Result<PageFlags, KernelError> {
-        let breakdown = VirtualAddressBreakdown::new(page);
-
-        // SAFETY: Same invariants as other PageMapper methods.
-        let l4_table = unsafe { &*self.l4_table };
-        let l4_entry = &l4_table[breakdown.l4_index];
-        if !l4_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
-        }
-
-        let l3_phys = l3_phys_from_entry(l4_entry, page)?;
-        // SAFETY: Physical address from a present L4 entry, converted to virtual via
-        // identity-mapped region.
-        let l3_table = unsafe { &*(super::phys_to_virt_addr(l3_phys as u64) as *const PageTable) };
-        let l3_entry = &l3_table[breakdown.l3_index];
-        if !l3_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
-        }
-
-        let l2_phys = l3_phys_from_entry(l3_entry, page)?;
-        // SAFETY: Physical address from a present L3 entry, converted to virtual via
-        // identity-mapped region.
-        let l2_table = unsafe { &*(super::phys_to_virt_addr(l2_phys as u64) as *const PageTable) };
-        let l2_entry = &l2_table[breakdown.l2_index];
-        if !l2_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
-        }
-
-        let l1_phys = l3_phys_from_entry(l2_entry, page)?;
-        // SAFETY: Physical address from a present L2 entry, converted to virtual via
-        // identity-mapped region.
-        let l1_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l1_phys as u64) as *mut PageTable) };
-        let entry = &mut l1_table[breakdown.l1_index];
+        let entry = self.leaf_entry_split(page)?;
 
         let frame = entry.frame().ok_or(KernelError::UnmappedMemory {
             addr: page.as_u64() as usize,
@@ -646,112 +536,265 @@ impl PageMapper {
     }
 
     /// Unmap a page
+    ///
+    /// A 2MB or 1GB page covering `page` is split first and only the 4KB
+    /// page at `page` is removed. Use `unmap_large_page` to drop a whole
+    /// large page at once.
     pub fn unmap_page(&mut self, page: VirtualAddress) -> Result<FrameNumber, KernelError> {
+        let entry = self.leaf_entry_split(page)?;
+
+        // Unmap the page
+        let frame = entry.frame().ok_or(KernelError::UnmappedMemory {
+            addr: page.as_u64() as usize,
+        })?;
+        entry.clear();
+
+        // Flush the TLB entry for the unmapped page to ensure stale translations
+        // are not used.
+        flush_tlb_page(page);
+
+        Ok(frame)
+    }
+
+    /// Unmap the 2MB or 1GB page that starts at `page`.
+    ///
+    /// Returns the first backing frame and the size of the page that was
+    /// removed. Fails with `InvalidArgument` if `page` is mapped but is not
+    /// the start of a large page. Only the local TLB is flushed; kernel
+    /// mappings and address spaces live on other CPUs need a shootdown.
+    pub fn unmap_large_page(
+        &mut self,
+        page: VirtualAddress,
+    ) -> Result<(FrameNumber, PageSize), KernelError> {
+        let breakdown = VirtualAddressBreakdown::new(page);
+        let unmapped = KernelError::UnmappedMemory {
+            addr: page.as_u64() as usize,
+        };
+
+        // SAFETY: Same invariants as map_page.
+        let l4_table = unsafe { &mut *self.l4_table };
+        let l3_table = next_table_mut(&l4_table[breakdown.l4_index]).ok_or(unmapped)?;
+
+        let (entry, size) = if is_large(&l3_table[breakdown.l3_index]) {
+            (&mut l3_table[breakdown.l3_index], PageSize::Huge)
+        } else {
+            let l2_table = next_table_mut(&l3_table[breakdown.l3_index]).ok_or(unmapped)?;
+            if !is_large(&l2_table[breakdown.l2_index]) {
+                return Err(KernelError::InvalidArgument {
+                    name: "page",
+                    value: "not mapped by a large page",
+                });
+            }
+            (&mut l2_table[breakdown.l2_index], PageSize::Large)
+        };
+
+        if !page.as_u64().is_multiple_of(size as u64) {
+            return Err(KernelError::InvalidArgument {
+                name: "page",
+                value: "not the start of a large page",
+            });
+        }
+
+        let frame = entry.frame().ok_or(unmapped)?;
+        entry.clear();
+        flush_tlb_page(page);
+
+        Ok((frame, size))
+    }
+
+    /// Walk to the L1 entry for `page`, splitting a 1GB or 2MB page on the
+    /// way so the 4KB page can be changed on its own.
+    fn leaf_entry_split(
+        &mut self,
+        page: VirtualAddress,
+    ) -> Result<&'static mut PageTableEntry, KernelError> {
         let breakdown = VirtualAddressBreakdown::new(page);
+        let unmapped = KernelError::UnmappedMemory {
+            addr: page.as_u64() as usize,
+        };
 
-        // Walk the page table hierarchy
         // SAFETY: `self.l4_table` was validated by the caller of `PageMapper::new`
         // to point to a valid, mapped page table. Exclusive access is guaranteed by
         // the PageMapper ownership contract.
         let l4_table = unsafe { &mut *self.l4_table };
-        let l4_entry = &l4_table[breakdown.l4_index];
-        if !l4_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
-        }
+        let l3_table = next_table_mut(&l4_table[breakdown.l4_index]).ok_or(unmapped)?;
 
-        // `is_present()` returned true, so `addr()` is guaranteed to return `Some`.
-        let l3_phys = l4_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L4 entry has address",
-            actual: "no address",
-        })?;
-        // SAFETY: Physical address from a present L4 entry, converted to virtual via
-        // identity-mapped region.
-        let l3_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l3_phys.as_u64()) as *mut PageTable) };
-        let l3_entry = &l3_table[breakdown.l3_index];
-        if !l3_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
+        let l3_entry = &mut l3_table[breakdown.l3_index];
+        if is_large(l3_entry) {
+            split_large_page(l3_entry, PageSize::Large, page)?;
         }
+        let l2_table = next_table_mut(l3_entry).ok_or(unmapped)?;
 
-        let l2_phys = l3_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L3 entry has address",
-            actual: "no address",
-        })?;
-        // SAFETY: Physical address from a present L3 entry, converted to virtual via
-        // identity-mapped region.
-        let l2_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l2_phys.as_u64()) as *mut PageTable) };
-        let l2_entry = &l2_table[breakdown.l2_index];
-        if !l2_entry.is_present() {
-            return Err(KernelError::UnmappedMemory {
-                addr: page.as_u64() as usize,
-            });
+        let l2_entry = &mut l2_table[breakdown.l2_index];
+        if is_large(l2_entry) {
+            split_large_page(l2_entry, PageSize::Small, page)?;
         }
+        let l1_table = next_table_mut(l2_entry).ok_or(unmapped)?;
 
-        let l1_phys = l2_entry.addr().ok_or(KernelError::InvalidState {
-            expected: "L2 entry has address",
-            actual: "no address",
-        })?;
-        // SAFETY: Physical address from a present L2 entry, converted to virtual via
-        // identity-mapped region.
-        let l1_table =
-            unsafe { &mut *(super::phys_to_virt_addr(l1_phys.as_u64()) as *mut PageTable) };
+        Ok(&mut l1_table[breakdown.l1_index])
+    }
+}
 
-        // Unmap the page
-        let entry = &mut l1_table[breakdown.l1_index];
-        let frame = entry.frame().ok_or(KernelError::UnmappedMemory {
-            addr: page.as_u64() as usize,
-        })?;
-        entry.clear();
+/// Mask of the physical address bits in an entry.
+const ENTRY_ADDR_MASK: u64 = 0x000F_FFFF_FFFF_F000;
 
-        // Flush the TLB entry for the unmapped page to ensure stale translations
-        // are not used. Each architecture has its own invalidation instruction.
-        #[cfg(target_arch = "x86_64")]
-        crate::arch::x86_64::mmu::flush_tlb_address(page.as_u64());
+/// Whether a present L3 or L2 entry maps a 1GB or 2MB page rather than
+/// pointing at a table.
+fn is_large(entry: &PageTableEntry) -> bool {
+    entry.is_present() && entry.flags().contains(PageFlags::HUGE)
+}
 
-        #[cfg(target_arch = "aarch64")]
-        {
-            // SAFETY: TLBI invalidates a single TLB entry for the given virtual
-            // address. This is a non-destructive, privileged operation.
-            unsafe {
-                core::arch::asm!(
-                    "tlbi vaae1is, {0}",
-                    "dsb ish",
-                    "isb",
-                    in(reg) (page.as_u64() >> 12),
-                );
-            }
-        }
+/// Frame `index` 4KB frames into the large page `entry` maps, with the
+/// entry's flags minus `HUGE`.
+fn large_page_frame(entry: &PageTableEntry, index: usize) -> Option<(FrameNumber, PageFlags)> {
+    let base = entry.frame()?;
+    let flags = PageFlags(entry.flags().0 & !PageFlags::HUGE.0);
+    Some((FrameNumber::new(base.as_u64() + index as u64), flags))
+}
 
-        #[cfg(target_arch = "riscv64")]
-        {
-            // SAFETY: sfence.vma invalidates TLB entries for the given virtual
-            // address. This is a non-destructive, privileged operation.
-            unsafe {
-                core::arch::asm!(
-                    "sfence.vma {0}, zero",
-                    in(reg) page.as_u64(),
-                );
-            }
+/// The table a present, non-leaf entry points to.
+fn next_table(entry: &PageTableEntry) -> Option<&'static PageTable> {
+    next_table_mut(entry).map(|table| &*table)
+}
+
+/// The table a present, non-leaf entry points to, for modification.
+fn next_table_mut(entry: &PageTableEntry) -> Option<&'static mut PageTable> {
+    if is_large(entry) {
+        return None;
+    }
+    let phys = entry.addr()?;
+    // SAFETY: A present entry that is not a large page holds the physical
+    // address of a page table, which is reachable through the kernel's
+    // physical memory window. Callers hold the PageMapper, which gives them
+    // exclusive access to the hierarchy.
+    Some(unsafe { &mut *(super::phys_to_virt_addr(phys.as_u64()) as *mut PageTable) })
+}
+
+/// Get or create the table an intermediate entry points to.
+///
+/// Intermediate page table entries must include USER if the leaf mapping
+/// is user-accessible; x86_64 checks USER on all 4 levels.
+fn next_table_create(
+    entry: &mut PageTableEntry,
+    page: VirtualAddress,
+    flags: PageFlags,
+    allocator: &mut impl FrameAllocator,
+) -> Result<&'static mut PageTable, KernelError> {
+    let user = flags.contains(PageFlags::USER);
+
+    if is_large(entry) {
+        // A large page already covers this address
+        return Err(KernelError::AlreadyExists {
+            resource: "page mapping",
+            id: page.as_u64(),
+        });
+    }
+
+    if !entry.is_present() {
+        let frame = allocator
+            .allocate_frames(1, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: 1,
+                available: 0,
+            })?;
+        // Zero the new page table frame before use
+        // SAFETY: phys_to_virt_addr converts the physical frame address
+        // to a valid virtual address in the kernel's memory mapping; the
+        // frame is freshly allocated and exclusively owned.
+        unsafe {
+            let virt = super::phys_to_virt_addr(frame.as_u64() << 12);
+            core::ptr::write_bytes(virt as *mut u8, 0, 4096);
+        }
+        let intermediate_flags = if user {
+            PageFlags::PRESENT | PageFlags::WRITABLE | PageFlags::USER
+        } else {
+            PageFlags::PRESENT | PageFlags::WRITABLE
+        };
+        entry.set(frame, intermediate_flags);
+    } else if user && !entry.flags().contains(PageFlags::USER) {
+        // Existing entry needs USER bit added for user-space child mapping
+        let current_flags = entry.flags();
+        if let Some(addr) = entry.addr() {
+            entry.set_addr(addr, current_flags | PageFlags::USER);
         }
+    }
 
-        Ok(frame)
+    next_table_mut(entry).ok_or(KernelError::InvalidState {
+        expected: "intermediate entry present",
+        actual: "not present",
+    })
+}
+
+/// Replace the 1GB or 2MB page `entry` maps with a table of 512 entries
+/// of `child_size` that map the same memory with the same attributes.
+///
+/// `page` is any address inside the large page; its stale translation is
+/// flushed from the local TLB.
+fn split_large_page(
+    entry: &mut PageTableEntry,
+    child_size: PageSize,
+    page: VirtualAddress,
+) -> Result<(), KernelError> {
+    let frame = FRAME_ALLOCATOR
+        .lock()
+        .allocate_frames(1, None)
+        .map_err(|_| KernelError::OutOfMemory {
+            requested: 1,
+            available: 0,
+        })?;
+    // SAFETY: The frame was just allocated and is reachable through the
+    // kernel's physical memory window. Every entry is written below.
+    let table = unsafe { &mut *(super::phys_to_virt_addr(frame.as_u64() << 12) as *mut PageTable) };
+
+    let base = entry.entry & ENTRY_ADDR_MASK;
+    let attrs = entry.entry & !ENTRY_ADDR_MASK;
+    // Bit 7 of a 4KB entry is PAT, not HUGE
+    let child_attrs = match child_size {
+        PageSize::Small => attrs & !PageFlags::HUGE.0,
+        PageSize::Large | PageSize::Huge => attrs,
+    };
+    for (i, child) in table.iter_mut().enumerate() {
+        child.entry = (base + (i * child_size as usize) as u64) | child_attrs;
     }
+
+    entry.entry = (frame.as_u64() << 12) | (attrs & !PageFlags::HUGE.0);
+    flush_tlb_page(page);
+
+    Ok(())
 }
 
-/// Extract a physical address from a present page table entry.
-/// Returns the address as a raw u64 suitable for casting to a pointer.
-fn l3_phys_from_entry(entry: &PageTableEntry, page: VirtualAddress) -> Result<u64, KernelError> {
-    entry
-        .addr()
-        .map(|a| a.as_u64())
-        .ok_or(KernelError::UnmappedMemory {
-            addr: page.as_u64() as usize,
-        })
+/// Flush the local TLB entry for `page`. Each architecture has its own
+/// invalidation instruction; all of them drop a large-page translation when
+/// given any address inside it.
+fn flush_tlb_page(page: VirtualAddress) {
+    #[cfg(target_arch = "x86_64")]
+    crate::arch::x86_64::mmu::flush_tlb_address(page.as_u64());
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // SAFETY: TLBI invalidates a single TLB entry for the given virtual
+        // address. This is a non-destructive, privileged operation.
+        unsafe {
+            core::arch::asm!(
+                "tlbi vaae1is, {0}",
+                "dsb ish",
+                "isb",
+                in(reg) (page.as_u64() >> 12),
+            );
+        }
+    }
+
+    #[cfg(target_arch = "riscv64")]
+    {
+        // SAFETY: sfence.vma invalidates TLB entries for the given virtual
+        // address. This is a non-destructive, privileged operation.
+        unsafe {
+            core::arch::asm!(
+                "sfence.vma {0}, zero",
+                in(reg) page.as_u64(),
+            );
+        }
+    }
 }
 
 /// Frame allocator trait for page mapper
@@ -11,13 +11,14 @@ use core::sync::atomic::{AtomicU64, Ordering};
 extern crate alloc;
 
 #[cfg(feature = "alloc")]
-use alloc::{collections::BTreeMap, vec::Vec};
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
 
 use spin::Mutex;
 
 use super::{
     page_table::{FrameAllocator as PageFrameAllocator, PageMapper, PageTable, PAGE_TABLE_ENTRIES},
-    FrameAllocatorError, FrameNumber, PageFlags, VirtualAddress, FRAME_ALLOCATOR, FRAME_SIZE,
+    FrameAllocatorError, FrameNumber, PageFlags, PageSize, VirtualAddress, FRAME_ALLOCATOR,
+    FRAME_SIZE,
 };
 use crate::error::KernelError;
 
@@ -67,6 +68,31 @@ pub unsafe fn create_mapper_from_root_pub(page_table_root: u64) -> PageMapper {
     unsafe { create_mapper_from_root(page_table_root) }
 }
 
+/// Remove `num_pages` 4KB pages starting at `start` from the page table.
+///
+/// 2MB pages that lie entirely inside the range are dropped as a whole
+/// instead of being split first. Address spaces only use 2MB large pages,
+/// never 1GB ones. Errors are ignored: a page may never have been installed
+/// in the hardware table (e.g., if map_region was called before the page
+/// table root was set).
+fn unmap_range(mapper: &mut PageMapper, start: u64, num_pages: usize) {
+    const LARGE_PAGE_FRAMES: usize = PageSize::Large as usize / FRAME_SIZE;
+
+    let mut i = 0;
+    while i < num_pages {
+        let vaddr = VirtualAddress(start + (i as u64) * 4096);
+        if vaddr.0.is_multiple_of(PageSize::Large as u64)
+            && num_pages - i >= LARGE_PAGE_FRAMES
+            && mapper.unmap_large_page(vaddr).is_ok()
+        {
+            i += LARGE_PAGE_FRAMES;
+            continue;
+        }
+        let _ = mapper.unmap_page(vaddr);
+        i += 1;
+    }
+}
+
 /// Free all user-space page table frames in a page table hierarchy.
 ///
 /// Walks the L4 table and for each **user-space** L4 entry (indices 0..256),
@@ -254,6 +280,32 @@ fn free_user_page_table_subtrees(l4_phys: u64) {
     }
 }
 
+/// Give the frames of a user mapping back to the allocator. A frame KSM
+/// merged is only freed by the last page sharing it.
+#[cfg(feature = "alloc")]
+fn free_user_frames(frames: &[FrameNumber]) {
+    let allocator = FRAME_ALLOCATOR.lock();
+    for &frame in frames {
+        if super::ksm::release(frame) {
+            let _ = allocator.free_frames(frame, 1);
+        }
+    }
+}
+
+/// The mapping containing `addr`, and the index of its page there.
+#[cfg(feature = "alloc")]
+fn page_of(
+    mappings: &mut BTreeMap<VirtualAddress, VirtualMapping>,
+    addr: u64,
+) -> Option<(&mut VirtualMapping, usize)> {
+    let (_, mapping) = mappings.range_mut(..=VirtualAddress(addr)).next_back()?;
+    if !mapping.contains(VirtualAddress(addr)) {
+        return None;
+    }
+    let index = ((addr - mapping.start.0) / FRAME_SIZE as u64) as usize;
+    Some((mapping, index))
+}
+
 /// Memory mapping types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MappingType {
@@ -287,6 +339,10 @@ pub struct VirtualMapping {
     /// Backing physical frames (if mapped)
     #[cfg(feature = "alloc")]
     pub physical_frames: Vec<super::FrameNumber>,
+    /// Path of the file the contents were loaded from (an executable or a
+    /// file mapped with mmap), shown by `/proc/<pid>/maps`
+    #[cfg(feature = "alloc")]
+    pub backing: Option<Arc<str>>,
 }
 
 impl VirtualMapping {
@@ -313,6 +369,8 @@ impl VirtualMapping {
             flags,
             #[cfg(feature = "alloc")]
             physical_frames: Vec::new(),
+            #[cfg(feature = "alloc")]
+            backing: None,
         }
     }
 
@@ -325,6 +383,13 @@ impl VirtualMapping {
     pub fn end(&self) -> VirtualAddress {
         VirtualAddress(self.start.0 + self.size as u64)
     }
+
+    /// Whether the backing frames belong to this mapping and are freed with
+    /// it. Device mappings alias memory owned elsewhere (MMIO, framebuffers,
+    /// packet rings shared with a driver).
+    pub fn owns_frames(&self) -> bool {
+        self.mapping_type != MappingType::Device
+    }
 }
 
 /// Virtual Address Space for a process
@@ -727,20 +792,13 @@ impl VirtualAddressSpace {
                 let mut mapper = unsafe { create_mapper_from_root(pt_root) };
 
                 for (_, mapping) in mappings.iter() {
-                    let num_pages = mapping.size / 4096;
-                    for i in 0..num_pages {
-                        let vaddr = VirtualAddress(mapping.start.0 + (i as u64) * 4096);
-                        let _ = mapper.unmap_page(vaddr);
-                    }
+                    unmap_range(&mut mapper, mapping.start.0, mapping.size / 4096);
                 }
             }
 
             // Free physical frames for each mapping
-            for (_, mapping) in mappings.iter() {
-                let allocator = FRAME_ALLOCATOR.lock();
-                for &frame in &mapping.physical_frames {
-                    let _ = allocator.free_frames(frame, 1);
-                }
+            for (_, mapping) in mappings.iter().filter(|(_, m)| m.owns_frames()) {
+                free_user_frames(&mapping.physical_frames);
             }
 
             // Clear all mappings
@@ -909,13 +967,29 @@ impl VirtualAddressSpace {
         let mut mapper = unsafe { create_mapper_from_root(pt_root) };
         let mut alloc = VasFrameAllocator;
 
-        // Build frame list from the physical region
-        let mut physical_frames = Vec::with_capacity(num_pages);
-        for i in 0..num_pages {
-            let frame = FrameNumber::new((aligned_phys >> 12) + i as u64);
-            physical_frames.push(frame);
+        // Build frame list from the physical region. Stretches where both
+        // addresses sit on a 2MB boundary are promoted to 2MB pages, which
+        // keeps a framebuffer from filling the TLB with 4KB entries.
+        const LARGE_PAGE_FRAMES: usize = PageSize::Large as usize / FRAME_SIZE;
+        let physical_frames: Vec<FrameNumber> = (0..num_pages as u64)
+            .map(|i| FrameNumber::new((aligned_phys >> 12) + i))
+            .collect();
+        let mut i = 0;
+        while i < num_pages {
+            let frame = physical_frames[i];
             let page_vaddr = VirtualAddress(vaddr.0 + (i as u64) * 4096);
+            if page_vaddr.0.is_multiple_of(PageSize::Large as u64)
+                && frame.as_u64().is_multiple_of(LARGE_PAGE_FRAMES as u64)
+                && num_pages - i >= LARGE_PAGE_FRAMES
+                && mapper
+                    .map_large_page(page_vaddr, frame, PageSize::Large, flags, &mut alloc)
+                    .is_ok()
+            {
+                i += LARGE_PAGE_FRAMES;
+                continue;
+            }
             mapper.map_page(page_vaddr, frame, flags, &mut alloc)?;
+            i += 1;
         }
 
         // Flush TLB
@@ -932,6 +1006,7 @@ impl VirtualAddressSpace {
             mapping_type: MappingType::Device,
             flags,
             physical_frames,
+            backing: None,
         };
         self.mappings.lock().insert(vaddr, mapping);
 
@@ -980,28 +1055,21 @@ impl VirtualAddressSpace {
             // the kernel's physical memory window. We hold the mappings lock,
             // ensuring exclusive page table modification for this VAS.
             let mut mapper = unsafe { create_mapper_from_root(pt_root) };
-
-            for i in 0..num_pages {
-                let vaddr = VirtualAddress(mapping.start.0 + (i as u64) * 4096);
-                // Ignore errors from unmap_page -- the page may not have been
-                // installed in the hardware table (e.g., if map_region was
-                // called before the page table root was set).
-                let _ = mapper.unmap_page(vaddr);
-            }
+            unmap_range(&mut mapper, mapping.start.0, num_pages);
         }
 
-        // Flush TLB for the unmapped range using batched flushes
+        // Flush TLB for the unmapped range using batched flushes. Other CPUs
+        // running threads of this process may still cache the old entries.
         let mut tlb_batch = TlbFlushBatch::new();
         for i in 0..num_pages {
             let vaddr = mapping.start.0 + (i as u64) * 4096;
             tlb_batch.add(vaddr);
         }
-        tlb_batch.flush();
+        tlb_batch.flush_with_shootdown();
 
         // Free the physical frames
-        let frame_allocator = FRAME_ALLOCATOR.lock();
-        for frame in mapping.physical_frames {
-            let _ = frame_allocator.free_frames(frame, 1);
+        if mapping.owns_frames() {
+            free_user_frames(&mapping.physical_frames);
         }
 
         Ok(())
@@ -1090,26 +1158,26 @@ impl VirtualAddressSpace {
         if pt_root != 0 {
             // SAFETY: pt_root is a valid L4 page table address set during VAS::init().
             let mut mapper = unsafe { create_mapper_from_root(pt_root) };
-            for i in unmap_page_start..unmap_page_end {
-                let vaddr = VirtualAddress(m_start + (i as u64) * 4096);
-                let _ = mapper.unmap_page(vaddr);
-            }
+            unmap_range(
+                &mut mapper,
+                m_start + (unmap_page_start as u64) * 4096,
+                unmap_page_count,
+            );
         }
 
-        // Flush TLB for unmapped pages using batched flushes
+        // Flush TLB for unmapped pages using batched flushes, on every CPU
         let mut tlb_batch = TlbFlushBatch::new();
         for i in unmap_page_start..unmap_page_end {
             let vaddr = m_start + (i as u64) * 4096;
             tlb_batch.add(vaddr);
         }
-        tlb_batch.flush();
+        tlb_batch.flush_with_shootdown();
 
         // Free the physical frames for the unmapped range
-        {
-            let frame_allocator = FRAME_ALLOCATOR.lock();
-            for i in unmap_page_start..unmap_page_end.min(mapping.physical_frames.len()) {
-                let _ = frame_allocator.free_frames(mapping.physical_frames[i], 1);
-            }
+        if mapping.owns_frames() {
+            let freed = unmap_page_start.min(mapping.physical_frames.len())
+                ..unmap_page_end.min(mapping.physical_frames.len());
+            free_user_frames(&mapping.physical_frames[freed]);
         }
 
         // Re-insert the remaining parts of the mapping
@@ -1119,6 +1187,7 @@ impl VirtualAddressSpace {
             let front_size = unmap_page_start * 4096;
             let mut front = VirtualMapping::new(containing_key, front_size, mapping.mapping_type);
             front.flags = mapping.flags;
+            front.backing = mapping.backing.clone();
             if unmap_page_start <= mapping.physical_frames.len() {
                 front.physical_frames = mapping.physical_frames[..unmap_page_start].to_vec();
             }
@@ -1136,6 +1205,7 @@ impl VirtualAddressSpace {
                 mapping.mapping_type,
             );
             back.flags = mapping.flags;
+            back.backing = mapping.backing.clone();
             if unmap_page_end < mapping.physical_frames.len() {
                 back.physical_frames = mapping.physical_frames[unmap_page_end..].to_vec();
             }
@@ -1157,6 +1227,178 @@ impl VirtualAddressSpace {
         None
     }
 
+    /// Copy the bytes at `addr` in this address space into `buf`, through
+    /// the kernel's physical memory window rather than the active page
+    /// tables, so it works on a process other than the current one.
+    ///
+    /// Fails on the first page that is unmapped or not yet backed.
+    #[cfg(feature = "alloc")]
+    pub fn read_bytes(&self, addr: VirtualAddress, buf: &mut [u8]) -> Result<(), KernelError> {
+        let mappings = self.mappings.lock();
+        let mut done = 0;
+        while done < buf.len() {
+            let cur = addr.0 + done as u64;
+            let unmapped = KernelError::UnmappedMemory { addr: cur as usize };
+            let Some((_, mapping)) = mappings.range(..=VirtualAddress(cur)).next_back() else {
+                return Err(unmapped);
+            };
+            if !mapping.contains(VirtualAddress(cur)) {
+                return Err(unmapped);
+            }
+            let offset = (cur - mapping.start.0) as usize;
+            let frame = match mapping.physical_frames.get(offset / FRAME_SIZE) {
+                Some(frame) if frame.as_u64() != 0 => *frame,
+                _ => return Err(unmapped),
+            };
+            let in_page = offset % FRAME_SIZE;
+            let len = (FRAME_SIZE - in_page).min(buf.len() - done);
+            let src = super::phys_to_virt_addr(frame.as_addr().as_u64() + in_page as u64);
+            // SAFETY: `frame` backs this mapping, and the kernel's physical
+            // memory window covers all of RAM; `len` stays within the page.
+            unsafe {
+                core::ptr::copy_nonoverlapping(src as *const u8, buf[done..].as_mut_ptr(), len);
+            }
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Whether all of `[start, start + len)` is covered by user mappings,
+    /// writable ones if `write` is set.
+    ///
+    /// Used by `syscall::uaccess` before copying to or from user memory.
+    /// Pages of a covered range may still be unbacked (demand paging).
+    #[cfg(feature = "alloc")]
+    pub fn user_range_accessible(&self, start: usize, len: usize, write: bool) -> bool {
+        let Some(end) = (start as u64).checked_add(len as u64) else {
+            return false;
+        };
+        let mappings = self.mappings.lock();
+        let mut addr = start as u64;
+        while addr < end {
+            let Some((_, mapping)) = mappings.range(..=VirtualAddress(addr)).next_back() else {
+                return false;
+            };
+            if !mapping.contains(VirtualAddress(addr))
+                || !mapping.flags.contains(PageFlags::USER)
+                || (write && !mapping.flags.contains(PageFlags::WRITABLE))
+            {
+                return false;
+            }
+            addr = mapping.end().0;
+        }
+        true
+    }
+
+    /// Record `path` as the file behind the mappings that start within
+    /// `size` bytes of `start`.
+    #[cfg(feature = "alloc")]
+    pub fn set_backing(&self, start: u64, size: usize, path: &str) {
+        let path: Arc<str> = Arc::from(path);
+        let range = VirtualAddress(start & !0xFFF)..VirtualAddress(start + size as u64);
+        for (_, mapping) in self.mappings.lock().range_mut(range) {
+            mapping.backing = Some(path.clone());
+        }
+    }
+
+    /// Back the read-only page at `vaddr` with frame `new` instead of `old`,
+    /// on every CPU. Fails, changing nothing, unless the page is still mapped
+    /// read-only to `old`. What becomes of either frame is up to the caller
+    /// (KSM, which merges identical pages this way).
+    #[cfg(feature = "alloc")]
+    pub fn replace_frame(
+        &self,
+        vaddr: u64,
+        old: FrameNumber,
+        new: FrameNumber,
+    ) -> Result<(), KernelError> {
+        let changed = KernelError::InvalidState {
+            expected: "read-only page backed by the expected frame",
+            actual: "page changed",
+        };
+        let mut mappings = self.mappings.lock();
+        let (mapping, index) = page_of(&mut mappings, vaddr).ok_or(changed)?;
+        if mapping.flags.contains(PageFlags::WRITABLE)
+            || mapping.physical_frames.get(index) != Some(&old)
+        {
+            return Err(changed);
+        }
+        let pt_root = self.page_table_root.load(Ordering::Acquire);
+        if pt_root == 0 {
+            return Err(changed);
+        }
+        // SAFETY: pt_root is the valid L4 page table of this VAS, and the
+        // mappings lock is held, so nothing else edits its user entries.
+        let mut mapper = unsafe { create_mapper_from_root(pt_root) };
+        let page = VirtualAddress(vaddr);
+        let (frame, flags) = mapper.translate_page(page)?;
+        if frame != old || flags.contains(PageFlags::WRITABLE) {
+            return Err(changed);
+        }
+        mapper.unmap_page(page)?;
+        mapper.map_page(page, new, flags, &mut VasFrameAllocator)?;
+        let mut tlb_batch = TlbFlushBatch::new();
+        tlb_batch.add(vaddr);
+        tlb_batch.flush_with_shootdown();
+        mapping.physical_frames[index] = new;
+        Ok(())
+    }
+
+    /// If KSM merged the page at `vaddr`, give it a private copy of the
+    /// frame, mapped with `flags`: the copy-on-write break that has to
+    /// happen before the page may be written. Returns whether it did.
+    #[cfg(feature = "alloc")]
+    pub fn unshare_page(&self, vaddr: u64, flags: PageFlags) -> Result<bool, KernelError> {
+        let mut mappings = self.mappings.lock();
+        let Some((mapping, index)) = page_of(&mut mappings, vaddr) else {
+            return Ok(false);
+        };
+        let Some(&merged) = mapping.physical_frames.get(index) else {
+            return Ok(false);
+        };
+        if !super::ksm::is_merged(merged) {
+            return Ok(false);
+        }
+        let pt_root = self.page_table_root.load(Ordering::Acquire);
+        if pt_root == 0 {
+            return Ok(false);
+        }
+
+        let copy = FRAME_ALLOCATOR
+            .lock()
+            .allocate_frames(1, None)
+            .map_err(|_| KernelError::OutOfMemory {
+                requested: FRAME_SIZE,
+                available: 0,
+            })?;
+        // SAFETY: Both frames are whole RAM pages reached through the
+        // kernel's physical memory window; `copy` was just allocated, so
+        // the two do not overlap.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                super::phys_to_virt_addr(merged.as_u64() << 12) as *const u8,
+                super::phys_to_virt_addr(copy.as_u64() << 12) as *mut u8,
+                FRAME_SIZE,
+            );
+        }
+
+        // SAFETY: As in `replace_frame`.
+        let mut mapper = unsafe { create_mapper_from_root(pt_root) };
+        let page = VirtualAddress(vaddr & !(FRAME_SIZE as u64 - 1));
+        let _ = mapper.unmap_page(page);
+        mapper.map_page(page, copy, flags, &mut VasFrameAllocator)?;
+        let mut tlb_batch = TlbFlushBatch::new();
+        tlb_batch.add(page.0);
+        tlb_batch.flush_with_shootdown();
+        mapping.physical_frames[index] = copy;
+        drop(mappings);
+
+        if super::ksm::release(merged) {
+            let _ = FRAME_ALLOCATOR.lock().free_frames(merged, 1);
+        }
+        Ok(true)
+    }
+
     /// Get a reference to the underlying mappings BTreeMap.
     ///
     /// Used by COW fork to iterate user-space pages and by diagnostics.
@@ -1248,6 +1490,102 @@ impl VirtualAddressSpace {
         Ok(addr)
     }
 
+    /// Allocate a memory-mapped region backed by 2MB pages (`MAP_HUGETLB`).
+    ///
+    /// The region starts on a 2MB boundary and its size is rounded up to a
+    /// multiple of 2MB. Fails with `OutOfMemory`, leaving nothing mapped, if
+    /// aligned physical memory cannot be found for every page; `sys_mmap`
+    /// then falls back to `mmap`.
+    #[cfg(feature = "alloc")]
+    pub fn mmap_huge(
+        &self,
+        size: usize,
+        mapping_type: MappingType,
+    ) -> Result<VirtualAddress, KernelError> {
+        const LARGE_PAGE_SIZE: usize = PageSize::Large as usize;
+        const LARGE_PAGE_FRAMES: usize = LARGE_PAGE_SIZE / FRAME_SIZE;
+
+        let aligned_size = size.next_multiple_of(LARGE_PAGE_SIZE);
+        let count = aligned_size / LARGE_PAGE_SIZE;
+        let oom = KernelError::OutOfMemory {
+            requested: aligned_size,
+            available: 0,
+        };
+
+        let mut mappings = self.mappings.lock();
+
+        // Allocate every large frame first so a failure leaves nothing behind
+        let mut large_frames = Vec::with_capacity(count);
+        {
+            let frame_allocator = FRAME_ALLOCATOR.lock();
+            for _ in 0..count {
+                match frame_allocator.allocate_large_frame(PageSize::Large, None) {
+                    Ok(frame) => large_frames.push(frame),
+                    Err(_) => {
+                        for &f in &large_frames {
+                            frame_allocator.free_frames(f, LARGE_PAGE_FRAMES).ok();
+                        }
+                        return Err(oom);
+                    }
+                }
+            }
+        }
+
+        for &frame in &large_frames {
+            let virt = crate::mm::phys_to_virt_addr(frame.as_u64() << 12) as *mut u8;
+            // SAFETY: The frames were just allocated and are reachable through
+            // the kernel's physical memory window. POSIX requires anonymous
+            // mappings to be zero-filled.
+            unsafe {
+                core::ptr::write_bytes(virt, 0, LARGE_PAGE_SIZE);
+            }
+        }
+
+        // Reserve a 2MB-aligned range past the current mmap cursor
+        let cursor = self
+            .next_mmap_addr
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |addr| {
+                Some(addr.next_multiple_of(LARGE_PAGE_SIZE as u64) + aligned_size as u64)
+            })
+            .unwrap_or_else(|addr| addr);
+        let start = VirtualAddress(cursor.next_multiple_of(LARGE_PAGE_SIZE as u64));
+
+        let mut mapping = VirtualMapping::new(start, aligned_size, mapping_type);
+
+        let pt_root = self.page_table_root.load(Ordering::Acquire);
+        if pt_root != 0 {
+            // SAFETY: Same as map_region -- pt_root is a valid L4 page table
+            // and we hold the mappings lock.
+            let mut mapper = unsafe { create_mapper_from_root(pt_root) };
+            let mut alloc = VasFrameAllocator;
+
+            for (i, &frame) in large_frames.iter().enumerate() {
+                let vaddr = VirtualAddress(start.0 + (i * LARGE_PAGE_SIZE) as u64);
+                if let Err(e) =
+                    mapper.map_large_page(vaddr, frame, PageSize::Large, mapping.flags, &mut alloc)
+                {
+                    unmap_range(&mut mapper, start.0, i * LARGE_PAGE_FRAMES);
+                    let frame_allocator = FRAME_ALLOCATOR.lock();
+                    for &f in &large_frames {
+                        frame_allocator.free_frames(f, LARGE_PAGE_FRAMES).ok();
+                    }
+                    return Err(e);
+                }
+                crate::arch::tlb_flush_address(vaddr.0);
+            }
+        }
+
+        // Track the 4KB frames individually so partial munmap and teardown
+        // can free them the same way as for small pages
+        mapping.physical_frames = large_frames
+            .iter()
+            .flat_map(|f| (0..LARGE_PAGE_FRAMES as u64).map(|i| FrameNumber::new(f.as_u64() + i)))
+            .collect();
+        mappings.insert(start, mapping);
+
+        Ok(start)
+    }
+
     /// Return the base address of the user heap region.
     pub fn heap_start_addr(&self) -> u64 {
         self.heap_start.load(Ordering::Relaxed)
@@ -1398,6 +1736,29 @@ impl VirtualAddressSpace {
         Ok(new_vas)
     }
 
+    /// Whether any page in `[start, start+size)` is backed by a frame KSM
+    /// merged.
+    #[cfg(feature = "alloc")]
+    fn has_merged_pages(&self, start: VirtualAddress, size: usize) -> bool {
+        let end = start.0 + size as u64;
+        let mappings = self.mappings.lock();
+        let frames = mappings
+            .range(..VirtualAddress(end))
+            .map(|(_, m)| m)
+            .filter(|m| m.end().0 > start.0)
+            .flat_map(|m| {
+                let first = (start.0.saturating_sub(m.start.0) / FRAME_SIZE as u64) as usize;
+                let last = ((end.min(m.end().0) - m.start.0 + FRAME_SIZE as u64 - 1)
+                    / FRAME_SIZE as u64) as usize;
+                m.physical_frames
+                    .get(first..last.min(m.physical_frames.len()))
+                    .unwrap_or_default()
+                    .iter()
+                    .copied()
+            });
+        super::ksm::any_merged(frames)
+    }
+
     /// Update hardware page table entry flags for a region.
     ///
     /// Walks the page table for each page in `[start, start+size)` and updates
@@ -1432,9 +1793,15 @@ impl VirtualAddressSpace {
         // mappings lock implicitly via the caller's &self borrow.
         let mut mapper = unsafe { create_mapper_from_root(pt_root) };
 
+        // A page KSM merged must not become writable in place
+        let unshare = new_flags.contains(PageFlags::WRITABLE) && self.has_merged_pages(start, size);
+
         let num_pages = (size + 4095) / 4096;
         for i in 0..num_pages {
             let vaddr = VirtualAddress(start.0 + (i as u64) * 4096);
+            if unshare {
+                self.unshare_page(vaddr.0, new_flags)?;
+            }
             // Ignore errors for pages that aren't mapped in the hardware tables
             let _ = mapper.update_page_flags(vaddr, new_flags);
             crate::arch::tlb_flush_address(vaddr.0);
@@ -1540,20 +1907,13 @@ impl VirtualAddressSpace {
                 let mut mapper = unsafe { create_mapper_from_root(pt_root) };
 
                 for (_, mapping) in mappings.iter() {
-                    let num_pages = mapping.size / 4096;
-                    for i in 0..num_pages {
-                        let vaddr = VirtualAddress(mapping.start.0 + (i as u64) * 4096);
-                        let _ = mapper.unmap_page(vaddr);
-                    }
+                    unmap_range(&mut mapper, mapping.start.0, mapping.size / 4096);
                 }
             }
 
             // Free physical frames for each mapping
-            for (_, mapping) in mappings.iter() {
-                let frame_allocator = FRAME_ALLOCATOR.lock();
-                for frame in &mapping.physical_frames {
-                    frame_allocator.free_frames(*frame, 1).ok();
-                }
+            for (_, mapping) in mappings.iter().filter(|(_, m)| m.owns_frames()) {
+                free_user_frames(&mapping.physical_frames);
             }
 
             // Clear all mappings
@@ -1617,22 +1977,15 @@ impl VirtualAddressSpace {
 
                 for addr in &to_remove {
                     if let Some(mapping) = mappings.get(addr) {
-                        let num_pages = mapping.size / 4096;
-                        for i in 0..num_pages {
-                            let vaddr = VirtualAddress(mapping.start.0 + (i as u64) * 4096);
-                            let _ = mapper.unmap_page(vaddr);
-                        }
+                        unmap_range(&mut mapper, mapping.start.0, mapping.size / 4096);
                     }
                 }
             }
 
             // Free physical frames and remove mappings
             for addr in &to_remove {
-                if let Some(mapping) = mappings.get(addr) {
-                    let frame_allocator = FRAME_ALLOCATOR.lock();
-                    for frame in &mapping.physical_frames {
-                        frame_allocator.free_frames(*frame, 1).ok();
-                    }
+                if let Some(mapping) = mappings.get(addr).filter(|m| m.owns_frames()) {
+                    free_user_frames(&mapping.physical_frames);
                 }
             }
 
@@ -1687,6 +2040,11 @@ impl VirtualAddressSpace {
         self.stack_size.store(size as u64, Ordering::Release);
     }
 
+    /// Set the address kernel-chosen mmap allocations start from
+    pub fn set_mmap_base(&self, addr: usize) {
+        self.next_mmap_addr.store(addr as u64, Ordering::Release);
+    }
+
     /// Map a single page at a virtual address
     pub fn map_page(&mut self, vaddr: usize, flags: PageFlags) -> Result<(), KernelError> {
         use super::PAGE_SIZE;
@@ -1731,6 +2089,12 @@ impl VirtualAddressSpace {
                     let _ = mapper.update_page_flags(vaddr_obj, flags);
                     let _ = FRAME_ALLOCATOR.lock().free_frames(frame, 1);
                     crate::arch::tlb_flush_address(vaddr as u64);
+                    // Keep the recorded flags in step; user_range_accessible
+                    // checks them.
+                    #[cfg(feature = "alloc")]
+                    if let Some(mapping) = self.mappings.lock().get_mut(&vaddr_obj) {
+                        mapping.flags |= flags;
+                    }
                     return Ok(());
                 }
                 Err(e) => return Err(e),
@@ -1758,14 +2122,17 @@ impl VirtualAddressSpace {
 
     /// Map a 2MB huge page at the given virtual address.
     ///
-    /// Allocates 512 contiguous 4KB frames (= 2MB) and installs a single
+    /// Allocates 512 contiguous, 2MB-aligned 4KB frames and installs a single
     /// L2 page table entry with the HUGE flag set. This reduces TLB pressure
-    /// for large contiguous allocations (heap, framebuffer, DMA).
+    /// for large contiguous allocations (heap, ELF segments, DMA).
     ///
-    /// The virtual address must be 2MB-aligned.
+    /// The virtual address must be 2MB-aligned. Fails with `AlreadyExists`
+    /// if part of the range is already mapped with 4KB pages, and with
+    /// `OutOfMemory` if no aligned physical range is free; callers fall back
+    /// to `map_page` in both cases.
     pub fn map_huge_page(&mut self, vaddr: usize, flags: PageFlags) -> Result<(), KernelError> {
-        const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024; // 2MB
-        const HUGE_PAGE_FRAMES: usize = HUGE_PAGE_SIZE / 4096; // 512
+        const HUGE_PAGE_SIZE: usize = PageSize::Large as usize; // 2MB
+        const HUGE_PAGE_FRAMES: usize = HUGE_PAGE_SIZE / FRAME_SIZE; // 512
 
         if vaddr & (HUGE_PAGE_SIZE - 1) != 0 {
             return Err(KernelError::InvalidArgument {
@@ -1774,10 +2141,10 @@ impl VirtualAddressSpace {
             });
         }
 
-        // Allocate 512 contiguous frames (2MB).
+        // Allocate 512 contiguous frames (2MB) on a 2MB physical boundary.
         let frame = FRAME_ALLOCATOR
             .lock()
-            .allocate_frames(HUGE_PAGE_FRAMES, None)
+            .allocate_large_frame(PageSize::Large, None)
             .map_err(|_| KernelError::OutOfMemory {
                 requested: HUGE_PAGE_SIZE,
                 available: 0,
@@ -1791,8 +2158,6 @@ impl VirtualAddressSpace {
             core::ptr::write_bytes(virt, 0, HUGE_PAGE_SIZE);
         }
 
-        // Install the 2MB mapping with HUGE flag.
-        let huge_flags = PageFlags(flags.0 | PageFlags::HUGE.0);
         let vaddr_obj = VirtualAddress(vaddr as u64);
 
         let pt_root = self.page_table_root.load(Ordering::Acquire);
@@ -1800,17 +2165,25 @@ impl VirtualAddressSpace {
             // SAFETY: Same as map_page -- pt_root is a valid L4 page table.
             let mut mapper = unsafe { create_mapper_from_root(pt_root) };
             let mut alloc = VasFrameAllocator;
-            mapper.map_page(vaddr_obj, frame, huge_flags, &mut alloc)?;
+            if let Err(e) =
+                mapper.map_large_page(vaddr_obj, frame, PageSize::Large, flags, &mut alloc)
+            {
+                let _ = FRAME_ALLOCATOR.lock().free_frames(frame, HUGE_PAGE_FRAMES);
+                return Err(e);
+            }
             crate::arch::tlb_flush_address(vaddr as u64);
         }
 
-        // Record the mapping.
+        // Record the mapping. Every 4KB frame is listed so that unmapping
+        // part of the page frees exactly the frames it covered.
         #[cfg(feature = "alloc")]
         {
             let mut mappings = self.mappings.lock();
             let mut new_mapping = VirtualMapping::new(vaddr_obj, HUGE_PAGE_SIZE, MappingType::Data);
-            new_mapping.physical_frames.push(frame);
-            new_mapping.flags = huge_flags;
+            new_mapping.physical_frames = (0..HUGE_PAGE_FRAMES as u64)
+                .map(|i| FrameNumber::new(frame.as_u64() + i))
+                .collect();
+            new_mapping.flags = flags;
             mappings.insert(vaddr_obj, new_mapping);
         }
 
@@ -1845,13 +2218,26 @@ pub fn map_physical_region_user(
 
     let memory_space = proc.memory_space.lock();
 
-    // Allocate a virtual address range from the mmap region
+    // Allocate a virtual address range from the mmap region. Regions of
+    // 2MB or more start at the same offset within a 2MB page as the
+    // physical address, so map_physical_region can use 2MB pages.
+    const LARGE_PAGE_SIZE: u64 = PageSize::Large as u64;
     let aligned_size = ((size + 4095) / 4096) * 4096;
-    let vaddr = VirtualAddress(
-        memory_space
-            .next_mmap_addr
-            .fetch_add(aligned_size as u64, Ordering::Relaxed),
-    );
+    let phys_offset = phys_addr & (LARGE_PAGE_SIZE - 1) & !0xFFF;
+    let place = |addr: u64| {
+        if aligned_size as u64 >= LARGE_PAGE_SIZE {
+            (addr - phys_offset).next_multiple_of(LARGE_PAGE_SIZE) + phys_offset
+        } else {
+            addr
+        }
+    };
+    let cursor = memory_space
+        .next_mmap_addr
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |addr| {
+            Some(place(addr) + aligned_size as u64)
+        })
+        .unwrap_or_else(|addr| addr);
+    let vaddr = VirtualAddress(place(cursor));
 
     // Map the physical frames
     #[cfg(feature = "alloc")]
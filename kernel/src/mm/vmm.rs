@@ -160,67 +160,41 @@ impl VirtualMemoryManager {
     /// Map a 2MB large page
     fn map_large_page(
         &mut self,
-        _virt: VirtualAddress,
-        _phys: PhysicalAddress,
-        _flags: PageFlags,
+        virt: VirtualAddress,
+        phys: PhysicalAddress,
+        flags: PageFlags,
     ) -> Result<(), KernelError> {
-        // For large pages, we need to set the page directory entry directly
-        // This is architecture-specific
-        #[cfg(target_arch = "x86_64")]
-        {
-            // Large page mappings use the HUGE flag
-            let _frame = FrameNumber::new(_phys.as_u64() >> 21);
-            let _large_flags = _flags | PageFlags::HUGE;
-            // Would map at PD level instead of PT level
-            println!(
-                "[VMM] Mapping large page 0x{:x} -> 0x{:x}",
-                _virt.as_u64(),
-                _phys.as_u64()
-            );
-        }
-
-        #[cfg(not(target_arch = "x86_64"))]
-        {
-            println!(
-                "[VMM] Mapping large page 0x{:x} -> 0x{:x}",
-                _virt.as_u64(),
-                _phys.as_u64()
-            );
-        }
-
-        Ok(())
+        // A single L2 entry with the HUGE flag maps the whole 2MB
+        let frame = FrameNumber::new(phys.as_u64() >> 12);
+        let mapper = self.get_or_create_mapper()?;
+        let mut frame_allocator_wrapper = FrameAllocatorWrapper;
+        mapper.map_large_page(
+            virt,
+            frame,
+            PageSize::Large,
+            flags,
+            &mut frame_allocator_wrapper,
+        )
     }
 
     /// Map a 1GB huge page
     fn map_huge_page(
         &mut self,
-        _virt: VirtualAddress,
-        _phys: PhysicalAddress,
-        _flags: PageFlags,
+        virt: VirtualAddress,
+        phys: PhysicalAddress,
+        flags: PageFlags,
     ) -> Result<(), KernelError> {
-        // For huge pages, we need to set the page directory pointer entry directly
-        #[cfg(target_arch = "x86_64")]
-        {
-            // Huge page mappings at PDP level
-            let _frame = FrameNumber::new(_phys.as_u64() >> 30);
-            let _huge_flags = _flags | PageFlags::HUGE;
-            println!(
-                "[VMM] Mapping huge page 0x{:x} -> 0x{:x}",
-                _virt.as_u64(),
-                _phys.as_u64()
-            );
-        }
-
-        #[cfg(not(target_arch = "x86_64"))]
-        {
-            println!(
-                "[VMM] Mapping huge page 0x{:x} -> 0x{:x}",
-                _virt.as_u64(),
-                _phys.as_u64()
-            );
-        }
-
-        Ok(())
+        // A single L3 entry with the HUGE flag maps the whole 1GB
+        let frame = FrameNumber::new(phys.as_u64() >> 12);
+        let mapper = self.get_or_create_mapper()?;
+        let mut frame_allocator_wrapper = FrameAllocatorWrapper;
+        mapper.map_large_page(
+            virt,
+            frame,
+            PageSize::Huge,
+            flags,
+            &mut frame_allocator_wrapper,
+        )
     }
 
     /// Unmap a virtual address
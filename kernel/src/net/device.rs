@@ -6,7 +6,7 @@
 
 #![allow(clippy::derivable_impls)]
 
-use alloc::{boxed::Box, string::String, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
 
 use spin::Mutex;
 
@@ -21,6 +21,8 @@ pub struct DeviceCapabilities {
     pub supports_checksum_offload: bool,
     pub supports_tso: bool, // TCP Segmentation Offload
     pub supports_lro: bool, // Large Receive Offload
+    /// Transmits straight from a list of page frames (no assembly copy)
+    pub supports_scatter_gather: bool,
 }
 
 impl Default for DeviceCapabilities {
@@ -31,6 +33,7 @@ impl Default for DeviceCapabilities {
             supports_checksum_offload: false,
             supports_tso: false,
             supports_lro: false,
+            supports_scatter_gather: false,
         }
     }
 }
@@ -104,23 +107,36 @@ pub trait NetworkDevice: Send {
     }
 }
 
+/// Name of the loopback interface
+pub const LOOPBACK: &str = "lo0";
+
+/// Interface flags, as in Linux `<net/if.h>`
+pub const IFF_UP: u32 = 0x1;
+pub const IFF_BROADCAST: u32 = 0x2;
+pub const IFF_LOOPBACK: u32 = 0x8;
+pub const IFF_RUNNING: u32 = 0x40;
+pub const IFF_MULTICAST: u32 = 0x1000;
+
+/// Smallest MTU an interface accepts (the IPv4 minimum)
+pub const MIN_MTU: usize = 68;
+
 /// Loopback device implementation
 pub struct LoopbackDevice {
     name: String,
     mac: MacAddress,
     state: DeviceState,
     stats: DeviceStatistics,
-    queue: Vec<Packet>,
+    queue: VecDeque<Packet>,
 }
 
 impl LoopbackDevice {
     pub fn new() -> Self {
         Self {
-            name: String::from("lo0"),
+            name: String::from(LOOPBACK),
             mac: MacAddress([0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
             state: DeviceState::Down,
             stats: DeviceStatistics::default(),
-            queue: Vec::new(),
+            queue: VecDeque::new(),
         }
     }
 }
@@ -147,6 +163,8 @@ impl NetworkDevice for LoopbackDevice {
             supports_checksum_offload: true,
             supports_tso: false,
             supports_lro: false,
+            // Loopback copies packets in memory, so frames cost nothing extra
+            supports_scatter_gather: true,
         }
     }
 
@@ -173,7 +191,7 @@ impl NetworkDevice for LoopbackDevice {
         }
 
         // Loopback: immediately queue for receive
-        self.queue.push(packet.clone());
+        self.queue.push_back(packet.clone());
         self.stats.tx_packets += 1;
         self.stats.tx_bytes += packet.len() as u64;
 
@@ -185,7 +203,7 @@ impl NetworkDevice for LoopbackDevice {
             return Ok(None);
         }
 
-        if let Some(packet) = self.queue.pop() {
+        if let Some(packet) = self.queue.pop_front() {
             self.stats.rx_packets += 1;
             self.stats.rx_bytes += packet.len() as u64;
             Ok(Some(packet))
@@ -269,8 +287,73 @@ impl NetworkDevice for EthernetDevice {
     }
 }
 
+/// A registered device and its interface settings
+struct Interface {
+    device: Box<dyn NetworkDevice>,
+    /// Interface index (`if_nametoindex`), from 1, never reused
+    index: u32,
+    /// Configured MTU, at most the device's maximum
+    mtu: usize,
+}
+
+impl Interface {
+    fn new(device: Box<dyn NetworkDevice>, index: u32) -> Self {
+        let mtu = device.mtu();
+        Self { device, index, mtu }
+    }
+
+    fn info(&self) -> InterfaceInfo {
+        let dev = self.device.as_ref();
+        let state = dev.state();
+        let mut flags = 0;
+        if matches!(state, DeviceState::Up | DeviceState::Dormant) {
+            flags |= IFF_UP;
+        }
+        if state == DeviceState::Up {
+            flags |= IFF_RUNNING;
+        }
+        if dev.name() == LOOPBACK {
+            flags |= IFF_LOOPBACK;
+        } else {
+            flags |= IFF_BROADCAST | IFF_MULTICAST;
+        }
+        InterfaceInfo {
+            name: String::from(dev.name()),
+            index: self.index,
+            mac: dev.mac_address(),
+            mtu: self.mtu,
+            max_mtu: dev.capabilities().max_transmission_unit,
+            state,
+            flags,
+            stats: dev.statistics(),
+        }
+    }
+}
+
+/// Snapshot of an interface, for listing and configuration
+#[derive(Debug, Clone)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub index: u32,
+    pub mac: MacAddress,
+    pub mtu: usize,
+    /// Largest MTU the device supports
+    pub max_mtu: usize,
+    pub state: DeviceState,
+    /// `IFF_*` flags
+    pub flags: u32,
+    pub stats: DeviceStatistics,
+}
+
 /// Device registry protected by Mutex for safe concurrent access
-static DEVICES: Mutex<Option<Vec<Box<dyn NetworkDevice>>>> = Mutex::new(None);
+static DEVICES: Mutex<Option<Vec<Interface>>> = Mutex::new(None);
+
+/// Index of the next registered device
+static NEXT_INDEX: core::sync::atomic::AtomicU32 = core::sync::atomic::AtomicU32::new(1);
+
+fn next_index() -> u32 {
+    NEXT_INDEX.fetch_add(1, core::sync::atomic::Ordering::Relaxed)
+}
 
 /// Initialize device subsystem
 pub fn init() -> Result<(), KernelError> {
@@ -282,7 +365,7 @@ pub fn init() -> Result<(), KernelError> {
     // Create and register loopback device
     let mut lo = LoopbackDevice::new();
     lo.set_state(DeviceState::Up)?;
-    device_list.push(Box::new(lo) as Box<dyn NetworkDevice>);
+    device_list.push(Interface::new(Box::new(lo), next_index()));
 
     *devices_lock = Some(device_list);
 
@@ -294,8 +377,14 @@ pub fn init() -> Result<(), KernelError> {
 pub fn register_device(device: Box<dyn NetworkDevice>) -> Result<(), KernelError> {
     let mut devices_lock = DEVICES.lock();
     if let Some(ref mut devices) = *devices_lock {
+        if devices.iter().any(|i| i.device.name() == device.name()) {
+            return Err(KernelError::AlreadyExists {
+                resource: "network interface",
+                id: 0,
+            });
+        }
         println!("[NETDEV] Registering device: {}", device.name());
-        devices.push(device);
+        devices.push(Interface::new(device, next_index()));
         Ok(())
     } else {
         Err(KernelError::InvalidState {
@@ -311,8 +400,8 @@ pub fn with_device<R, F: FnOnce(&dyn NetworkDevice) -> R>(name: &str, f: F) -> O
     if let Some(ref devices) = *devices_lock {
         devices
             .iter()
-            .find(|d| d.name() == name)
-            .map(|d| f(d.as_ref()))
+            .find(|i| i.device.name() == name)
+            .map(|i| f(i.device.as_ref()))
     } else {
         None
     }
@@ -324,8 +413,8 @@ pub fn with_device_mut<R, F: FnOnce(&mut dyn NetworkDevice) -> R>(name: &str, f:
     if let Some(ref mut devices) = *devices_lock {
         devices
             .iter_mut()
-            .find(|d| d.name() == name)
-            .map(|d| f(d.as_mut()))
+            .find(|i| i.device.name() == name)
+            .map(|i| f(i.device.as_mut()))
     } else {
         None
     }
@@ -335,12 +424,106 @@ pub fn with_device_mut<R, F: FnOnce(&mut dyn NetworkDevice) -> R>(name: &str, f:
 pub fn list_devices() -> Vec<String> {
     let devices_lock = DEVICES.lock();
     if let Some(ref devices) = *devices_lock {
-        devices.iter().map(|d| String::from(d.name())).collect()
+        devices
+            .iter()
+            .map(|i| String::from(i.device.name()))
+            .collect()
     } else {
         Vec::new()
     }
 }
 
+/// All interfaces, in index order.
+pub fn interfaces() -> Vec<InterfaceInfo> {
+    DEVICES
+        .lock()
+        .as_ref()
+        .map(|devices| devices.iter().map(Interface::info).collect())
+        .unwrap_or_default()
+}
+
+/// The interface called `name`.
+pub fn interface(name: &str) -> Option<InterfaceInfo> {
+    let devices = DEVICES.lock();
+    devices
+        .as_ref()?
+        .iter()
+        .find(|i| i.device.name() == name)
+        .map(Interface::info)
+}
+
+/// The interface with index `index`.
+pub fn interface_by_index(index: u32) -> Option<InterfaceInfo> {
+    let devices = DEVICES.lock();
+    devices
+        .as_ref()?
+        .iter()
+        .find(|i| i.index == index)
+        .map(Interface::info)
+}
+
+/// Configured MTU of interface `name`.
+pub fn mtu(name: &str) -> Option<usize> {
+    let devices = DEVICES.lock();
+    devices
+        .as_ref()?
+        .iter()
+        .find(|i| i.device.name() == name)
+        .map(|i| i.mtu)
+}
+
+fn not_found(name: &str) -> KernelError {
+    KernelError::NotFound {
+        resource: "network interface",
+        id: name.len() as u64,
+    }
+}
+
+/// Set the MTU of interface `name`, between [`MIN_MTU`] and the device's
+/// maximum.
+pub fn set_mtu(name: &str, mtu: usize) -> Result<(), KernelError> {
+    let mut devices = DEVICES.lock();
+    let interface = devices
+        .as_mut()
+        .and_then(|d| d.iter_mut().find(|i| i.device.name() == name))
+        .ok_or_else(|| not_found(name))?;
+    if !(MIN_MTU..=interface.device.capabilities().max_transmission_unit).contains(&mtu) {
+        return Err(KernelError::InvalidArgument {
+            name: "mtu",
+            value: "out of range for the device",
+        });
+    }
+    interface.mtu = mtu;
+    Ok(())
+}
+
+/// Bring interface `name` up or take it down.
+pub fn set_up(name: &str, up: bool) -> Result<(), KernelError> {
+    let state = if up {
+        DeviceState::Up
+    } else {
+        DeviceState::Down
+    };
+    with_device_mut(name, |dev| dev.set_state(state)).ok_or_else(|| not_found(name))?
+}
+
+/// Hand frames sent to the loopback interface back to the stack.
+///
+/// Delivery is deferred to here rather than done inside the send, so a
+/// protocol handler that replies while holding its own locks does not
+/// re-enter itself.
+pub fn poll_loopback() {
+    let mut frames = Vec::new();
+    with_device_mut(LOOPBACK, |lo| {
+        while let Ok(Some(packet)) = lo.receive() {
+            frames.push(packet);
+        }
+    });
+    for frame in frames {
+        let _ = super::ethernet::dispatch_frame(LOOPBACK, frame.data(), &MacAddress::ZERO);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,6 +537,30 @@ mod tests {
         assert_eq!(lo.state(), DeviceState::Up);
     }
 
+    #[test]
+    fn test_loopback_fifo() {
+        let mut lo = LoopbackDevice::new();
+        lo.set_state(DeviceState::Up).unwrap();
+        lo.transmit(&Packet::from_bytes(&[1])).unwrap();
+        lo.transmit(&Packet::from_bytes(&[2])).unwrap();
+        assert_eq!(lo.receive().unwrap().unwrap().data(), &[1]);
+        assert_eq!(lo.receive().unwrap().unwrap().data(), &[2]);
+        assert!(lo.receive().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_interface_flags() {
+        let mut lo = LoopbackDevice::new();
+        lo.set_state(DeviceState::Up).unwrap();
+        let info = Interface::new(Box::new(lo), 1).info();
+        assert_eq!(info.flags, IFF_UP | IFF_RUNNING | IFF_LOOPBACK);
+        assert_eq!(info.mtu, 65536);
+
+        let eth = EthernetDevice::new(String::from("eth9"), MacAddress::ZERO);
+        let info = Interface::new(Box::new(eth), 2).info();
+        assert_eq!(info.flags, IFF_BROADCAST | IFF_MULTICAST);
+    }
+
     #[test]
     fn test_device_capabilities() {
         let caps = DeviceCapabilities::default();
@@ -102,10 +102,21 @@ pub fn is_for_us(frame_dst: &MacAddress, our_mac: &MacAddress) -> bool {
     *frame_dst == *our_mac || is_broadcast(frame_dst) || is_ipv6_multicast(frame_dst)
 }
 
-/// Dispatch a received Ethernet frame to the appropriate protocol handler.
+/// Dispatch a frame received on `interface` to the appropriate protocol
+/// handler.
 ///
-/// Routes frames to ARP or IP based on the EtherType field.
-pub fn dispatch_frame(data: &[u8], our_mac: &MacAddress) -> Result<(), KernelError> {
+/// Routes frames to ARP or IP based on the EtherType field. IPv4 packets
+/// pass the firewall's input hook first. Packet rings attached to the
+/// interface see the frame before any of that, and may divert it.
+pub fn dispatch_frame(
+    interface: &str,
+    data: &[u8],
+    our_mac: &MacAddress,
+) -> Result<(), KernelError> {
+    if super::packet_ring::deliver(interface, data) {
+        return Ok(());
+    }
+
     let frame = parse_frame(data)?;
 
     // Drop frames not addressed to us
@@ -124,6 +135,9 @@ pub fn dispatch_frame(data: &[u8], our_mac: &MacAddress) -> Result<(), KernelErr
                 let header_len = (ip_header.ihl as usize) * 4;
                 if frame.payload.len() >= header_len {
                     let ip_payload = &frame.payload[header_len..];
+                    if !super::firewall::hooks::input(interface, &ip_header, ip_payload) {
+                        return Ok(());
+                    }
                     let src = super::IpAddress::V4(ip_header.source);
                     let dst = super::IpAddress::V4(ip_header.destination);
 
@@ -253,6 +253,53 @@ impl FirewallEngine {
         }
     }
 
+    /// Insert a rule ID at `index` (clamped) in a filter table chain
+    pub fn insert_into_filter_chain(
+        &mut self,
+        chain_name: &str,
+        index: usize,
+        rule_id: u64,
+    ) -> bool {
+        if let Some(chain) = self.filter.get_chain_mut(chain_name) {
+            chain.insert_rule(index, rule_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove a rule from the rule engine and from every chain
+    pub fn delete_rule(&mut self, rule_id: u64) -> bool {
+        if self.rule_engine.remove_rule(rule_id).is_none() {
+            return false;
+        }
+        for table in [&mut self.filter, &mut self.nat, &mut self.mangle] {
+            for chain in &mut table.chains {
+                chain.remove_rule(rule_id);
+            }
+        }
+        true
+    }
+
+    /// Remove every rule of a filter table chain
+    pub fn flush_filter_chain(&mut self, chain_name: &str) -> bool {
+        let Some(chain) = self.filter.get_chain_mut(chain_name) else {
+            return false;
+        };
+        let rule_ids = core::mem::take(&mut chain.rule_ids);
+        for rule_id in rule_ids {
+            self.rule_engine.remove_rule(rule_id);
+        }
+        true
+    }
+
+    /// Zero the packet/byte counters of the engine and every rule
+    pub fn reset_counters(&mut self) {
+        self.total_packets = 0;
+        self.dropped_packets = 0;
+        self.rule_engine.reset_counters();
+    }
+
     /// Set the policy for a chain in the filter table
     pub fn set_filter_policy(&mut self, chain_name: &str, policy: ChainPolicy) -> bool {
         if let Some(chain) = self.filter.get_chain_mut(chain_name) {
@@ -351,7 +398,10 @@ impl FirewallEngine {
                             RuleAction::Accept => return Verdict::Accept,
                             RuleAction::Drop => return Verdict::Drop,
                             RuleAction::Reject => return Verdict::Reject,
-                            RuleAction::Log => continue, // Log and continue evaluation
+                            RuleAction::Log => {
+                                log_packet(hook, metadata);
+                                continue;
+                            }
                             RuleAction::Return => break, // Return to calling chain
                             _ => return Verdict::Accept,
                         }
@@ -370,6 +420,28 @@ impl FirewallEngine {
     }
 }
 
+/// Report a packet matched by a `Log` rule
+fn log_packet(_hook: HookPoint, _meta: &PacketMetadata) {
+    crate::println!(
+        "[FW] {:?} in={} out={} proto={:?} src={}.{}.{}.{}:{} dst={}.{}.{}.{}:{} len={}",
+        _hook,
+        _meta.in_interface,
+        _meta.out_interface,
+        _meta.protocol,
+        _meta.src_ip.0[0],
+        _meta.src_ip.0[1],
+        _meta.src_ip.0[2],
+        _meta.src_ip.0[3],
+        _meta.src_port,
+        _meta.dst_ip.0[0],
+        _meta.dst_ip.0[1],
+        _meta.dst_ip.0[2],
+        _meta.dst_ip.0[3],
+        _meta.dst_port,
+        _meta.packet_len
+    );
+}
+
 impl Default for FirewallEngine {
     fn default() -> Self {
         Self::new()
@@ -510,6 +582,48 @@ mod tests {
         assert_eq!(engine.mangle.chains.len(), 5);
     }
 
+    #[test]
+    fn test_engine_delete_and_flush() {
+        let mut engine = FirewallEngine::new();
+        let a = engine.add_rule(FirewallRule::new(
+            0,
+            super::super::rules::MatchCriteria::new(),
+            RuleAction::Drop,
+        ));
+        let b = engine.add_rule(FirewallRule::new(
+            0,
+            super::super::rules::MatchCriteria::new(),
+            RuleAction::Accept,
+        ));
+        assert!(engine.add_to_filter_chain("INPUT", a));
+        assert!(engine.insert_into_filter_chain("INPUT", 0, b));
+        assert_eq!(
+            engine.filter.get_chain("INPUT").unwrap().rule_ids,
+            alloc::vec![b, a]
+        );
+
+        // The ACCEPT rule inserted in front wins
+        let metadata = PacketMetadata::default();
+        assert_eq!(
+            engine.process_packet(HookPoint::Input, &metadata),
+            Verdict::Accept
+        );
+
+        assert!(engine.delete_rule(b));
+        assert!(!engine.delete_rule(b));
+        assert_eq!(
+            engine.process_packet(HookPoint::Input, &metadata),
+            Verdict::Drop
+        );
+
+        assert!(engine.flush_filter_chain("INPUT"));
+        assert_eq!(engine.rule_engine.rule_count(), 0);
+        assert_eq!(
+            engine.process_packet(HookPoint::Input, &metadata),
+            Verdict::Accept
+        );
+    }
+
     #[test]
     fn test_engine_process_accept_default() {
         let mut engine = FirewallEngine::new();
@@ -530,6 +530,11 @@ impl ConntrackTable {
         ConntrackState::New
     }
 
+    /// Iterate over the tracked connections
+    pub fn entries(&self) -> impl Iterator<Item = &ConntrackEntry> {
+        self.entries.values()
+    }
+
     /// Get statistics
     pub fn stats(&self) -> ConntrackStats {
         ConntrackStats {
@@ -0,0 +1,222 @@
+//! Packet path hooks
+//!
+//! The IPv4 receive path calls [`input`] for every packet before handing it
+//! to TCP/UDP, and [`ip::send`](crate::net::ip::send) calls [`output`] for
+//! every packet it builds. Each hook tracks the packet in the connection
+//! table, so rules can match on its state, and runs the chains of the hook
+//! point with the interface the packet arrived on or leaves by.
+//!
+//! Rejected inbound packets are answered with a TCP RST, or an ICMP port
+//! unreachable for other protocols; rejected or dropped outbound packets
+//! fail the send with `PermissionDenied`.
+
+use alloc::{string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{
+    chain::{self, HookPoint, Verdict},
+    conntrack::{self, ConntrackKey, ConntrackState},
+    rules::{PacketMetadata, Protocol},
+};
+use crate::{
+    error::KernelError,
+    net::{
+        ip::{IpProtocol, Ipv4Header},
+        IpAddress,
+    },
+};
+
+/// Seconds between sweeps of expired connection tracking entries
+const CONNTRACK_GC_INTERVAL: u64 = 60;
+
+/// Uptime (seconds) of the last conntrack sweep
+static LAST_GC: AtomicU64 = AtomicU64::new(0);
+
+/// ICMP destination unreachable, port unreachable
+const ICMP_DEST_UNREACHABLE: u8 = 3;
+const ICMP_PORT_UNREACHABLE: u8 = 3;
+
+/// Filter a packet received on `interface`. Returns whether it may be
+/// delivered.
+pub fn input(interface: &str, header: &Ipv4Header, payload: &[u8]) -> bool {
+    let mut meta = metadata(header, payload);
+    meta.in_interface = String::from(interface);
+    // Loopback packets were tracked when they were sent.
+    meta.conn_state = track(&meta, interface != crate::net::device::LOOPBACK);
+
+    match run(HookPoint::Input, &meta) {
+        Verdict::Accept => true,
+        Verdict::Reject => {
+            reject(header, payload);
+            false
+        }
+        Verdict::Drop | Verdict::Queue => false,
+    }
+}
+
+/// Filter a locally generated packet leaving by `interface`.
+pub fn output(interface: &str, header: &Ipv4Header, payload: &[u8]) -> Result<(), KernelError> {
+    let mut meta = metadata(header, payload);
+    meta.out_interface = String::from(interface);
+    meta.conn_state = track(&meta, true);
+
+    match run(HookPoint::Output, &meta) {
+        Verdict::Accept => Ok(()),
+        _ => Err(KernelError::PermissionDenied {
+            operation: "send packet blocked by firewall",
+        }),
+    }
+}
+
+/// Run the chains of `hook`; accept everything before the firewall is
+/// initialized.
+fn run(hook: HookPoint, meta: &PacketMetadata) -> Verdict {
+    chain::with_engine(|engine| engine.process_packet(hook, meta)).unwrap_or(Verdict::Accept)
+}
+
+/// Extract the header fields the rules match on.
+fn metadata(header: &Ipv4Header, payload: &[u8]) -> PacketMetadata {
+    let protocol = match header.protocol {
+        1 => Protocol::Icmp,
+        6 => Protocol::Tcp,
+        17 => Protocol::Udp,
+        _ => Protocol::Any,
+    };
+    let (src_port, dst_port) = match protocol {
+        Protocol::Tcp | Protocol::Udp if payload.len() >= 4 => (
+            u16::from_be_bytes([payload[0], payload[1]]),
+            u16::from_be_bytes([payload[2], payload[3]]),
+        ),
+        _ => (0, 0),
+    };
+    let tcp_flags = match protocol {
+        Protocol::Tcp if payload.len() > 13 => payload[13],
+        _ => 0,
+    };
+
+    PacketMetadata {
+        src_ip: header.source,
+        dst_ip: header.destination,
+        src_port,
+        dst_port,
+        protocol,
+        tcp_flags,
+        conn_state: None,
+        packet_len: header.total_length,
+        ..PacketMetadata::default()
+    }
+}
+
+/// Classify the packet against the connection table, recording it there
+/// when `record` is set.
+fn track(meta: &PacketMetadata, record: bool) -> Option<ConntrackState> {
+    let key = ConntrackKey::new(
+        meta.src_ip,
+        meta.dst_ip,
+        meta.src_port,
+        meta.dst_port,
+        ConntrackKey::protocol_to_num(meta.protocol),
+    );
+    let now = crate::timer::get_uptime_ms() / 1000;
+
+    conntrack::with_conntrack(|table| {
+        table.set_tick(now);
+        if now >= LAST_GC.load(Ordering::Relaxed) + CONNTRACK_GC_INTERVAL {
+            LAST_GC.store(now, Ordering::Relaxed);
+            table.gc();
+        }
+        if record {
+            table.track_packet(key, meta.packet_len as u64, meta.tcp_flags)
+        } else {
+            table.classify_packet(&key)
+        }
+    })
+}
+
+/// Answer a rejected inbound packet.
+fn reject(header: &Ipv4Header, payload: &[u8]) {
+    let sender = IpAddress::V4(header.source);
+    match header.protocol {
+        6 => crate::net::tcp::send_reset(sender, payload),
+        // Never answer an ICMP message with another one.
+        1 => {}
+        _ => {
+            let message = port_unreachable(header, payload);
+            let _ = crate::net::ip::send(sender, IpProtocol::Icmp, &message);
+        }
+    }
+}
+
+/// Build an ICMP port unreachable message quoting the offending packet's
+/// header and the first 8 bytes of its payload (RFC 792).
+fn port_unreachable(header: &Ipv4Header, payload: &[u8]) -> Vec<u8> {
+    let mut quoted = header.clone();
+    quoted.ihl = 5;
+    let quoted_header = quoted.to_bytes();
+    let quoted_payload = &payload[..payload.len().min(8)];
+
+    let mut message = Vec::with_capacity(8 + quoted_header.len() + quoted_payload.len());
+    // Type, code, checksum, unused
+    message.extend_from_slice(&[ICMP_DEST_UNREACHABLE, ICMP_PORT_UNREACHABLE]);
+    message.extend_from_slice(&[0; 6]);
+    message.extend_from_slice(&quoted_header);
+    message.extend_from_slice(quoted_payload);
+
+    let checksum = crate::net::multicast::internet_checksum(&message);
+    message[2..4].copy_from_slice(&checksum.to_be_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::Ipv4Address;
+
+    fn udp_header() -> Ipv4Header {
+        let mut header = Ipv4Header::new(
+            Ipv4Address::new(10, 0, 0, 2),
+            Ipv4Address::new(10, 0, 0, 1),
+            IpProtocol::Udp,
+        );
+        header.total_length = 20 + 12;
+        header
+    }
+
+    #[test]
+    fn test_metadata_udp() {
+        let payload = [0x13, 0x88, 0x00, 0x35, 0, 12, 0, 0, 1, 2, 3, 4];
+        let meta = metadata(&udp_header(), &payload);
+        assert_eq!(meta.protocol, Protocol::Udp);
+        assert_eq!(meta.src_port, 5000);
+        assert_eq!(meta.dst_port, 53);
+        assert_eq!(meta.tcp_flags, 0);
+        assert_eq!(meta.packet_len, 32);
+    }
+
+    #[test]
+    fn test_metadata_tcp_flags() {
+        let mut header = udp_header();
+        header.protocol = 6;
+        let mut segment = [0u8; 20];
+        segment[0..2].copy_from_slice(&40000u16.to_be_bytes());
+        segment[2..4].copy_from_slice(&22u16.to_be_bytes());
+        segment[13] = 0x02; // SYN
+        let meta = metadata(&header, &segment);
+        assert_eq!(meta.protocol, Protocol::Tcp);
+        assert_eq!(meta.dst_port, 22);
+        assert_eq!(meta.tcp_flags, 0x02);
+    }
+
+    #[test]
+    fn test_port_unreachable() {
+        let payload = [0x13, 0x88, 0x00, 0x35, 0, 12, 0, 0, 1, 2, 3, 4];
+        let message = port_unreachable(&udp_header(), &payload);
+        assert_eq!(message.len(), 8 + 20 + 8);
+        assert_eq!(message[0], ICMP_DEST_UNREACHABLE);
+        assert_eq!(message[1], ICMP_PORT_UNREACHABLE);
+        assert_eq!(&message[8 + 12..8 + 16], &[10, 0, 0, 2]);
+        assert_eq!(&message[28..], &payload[..8]);
+        // A correct checksum sums to zero
+        assert_eq!(crate::net::multicast::internet_checksum(&message), 0);
+    }
+}
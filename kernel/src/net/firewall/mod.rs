@@ -2,12 +2,14 @@
 //!
 //! Provides packet filtering, connection tracking, and NAT capabilities
 //! modeled on the Linux netfilter/iptables architecture with five hook
-//! points (PreRouting, Input, Forward, Output, PostRouting).
+//! points (PreRouting, Input, Forward, Output, PostRouting). The IPv4 path
+//! runs the Input and Output hooks through [`hooks`].
 
 #![allow(dead_code)]
 
 pub mod chain;
 pub mod conntrack;
+pub mod hooks;
 pub mod nat;
 pub mod rules;
 
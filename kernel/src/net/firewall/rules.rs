@@ -1,8 +1,9 @@
 //! Firewall rule matching and evaluation
 //!
 //! Provides rule definitions with match criteria (source/dest IP with CIDR,
-//! port ranges, protocol, TCP flags, connection state) and actions
-//! (Accept, Drop, Reject, Log, Jump, Masquerade, SNAT, DNAT).
+//! port ranges, protocol, TCP flags, connection state, input/output
+//! interface) and actions (Accept, Drop, Reject, Log, Jump, Masquerade,
+//! SNAT, DNAT).
 //! CIDR matching uses bitmask comparison for efficient subnet checks.
 
 #![allow(dead_code)]
@@ -217,6 +218,10 @@ pub struct MatchCriteria {
     pub negate_src: bool,
     /// Negate destination IP match
     pub negate_dst: bool,
+    /// Interface the packet arrived on (None = match any)
+    pub in_interface: Option<String>,
+    /// Interface the packet leaves by (None = match any)
+    pub out_interface: Option<String>,
 }
 
 impl MatchCriteria {
@@ -266,6 +271,18 @@ impl MatchCriteria {
         self.conn_state = Some(state);
         self
     }
+
+    /// Set input interface match
+    pub fn with_in_interface(mut self, interface: &str) -> Self {
+        self.in_interface = Some(String::from(interface));
+        self
+    }
+
+    /// Set output interface match
+    pub fn with_out_interface(mut self, interface: &str) -> Self {
+        self.out_interface = Some(String::from(interface));
+        self
+    }
 }
 
 // ============================================================================
@@ -322,6 +339,10 @@ pub struct PacketMetadata {
     pub conn_state: Option<ConntrackState>,
     /// Total packet length in bytes
     pub packet_len: u16,
+    /// Interface the packet arrived on (empty for outbound packets)
+    pub in_interface: String,
+    /// Interface the packet leaves by (empty for inbound packets)
+    pub out_interface: String,
 }
 
 impl Default for PacketMetadata {
@@ -335,6 +356,8 @@ impl Default for PacketMetadata {
             tcp_flags: 0,
             conn_state: None,
             packet_len: 0,
+            in_interface: String::new(),
+            out_interface: String::new(),
         }
     }
 }
@@ -443,6 +466,18 @@ impl FirewallRule {
             }
         }
 
+        // Interface checks
+        if let Some(ref interface) = self.criteria.in_interface {
+            if *interface != meta.in_interface {
+                return false;
+            }
+        }
+        if let Some(ref interface) = self.criteria.out_interface {
+            if *interface != meta.out_interface {
+                return false;
+            }
+        }
+
         true
     }
 
@@ -518,6 +553,13 @@ impl RuleEngine {
         None
     }
 
+    /// Zero the counters of every rule
+    pub fn reset_counters(&mut self) {
+        for rule in self.rules.values_mut() {
+            rule.reset_counters();
+        }
+    }
+
     /// Get all rules sorted by priority
     pub fn rules_by_priority(&self) -> Vec<&FirewallRule> {
         let mut rules: Vec<&FirewallRule> = self.rules.values().collect();
@@ -550,6 +592,8 @@ mod tests {
             tcp_flags: TcpFlags::SYN,
             conn_state: Some(ConntrackState::New),
             packet_len: 64,
+            in_interface: String::from("eth0"),
+            out_interface: String::new(),
         }
     }
 
@@ -683,6 +727,28 @@ mod tests {
         assert!(rule.matches_packet(&meta2));
     }
 
+    #[test]
+    fn test_rule_matches_interface() {
+        let rule = FirewallRule::new(
+            1,
+            MatchCriteria::new().with_in_interface("eth0"),
+            RuleAction::Drop,
+        );
+        let meta = test_metadata();
+        assert!(rule.matches_packet(&meta));
+
+        let mut meta2 = test_metadata();
+        meta2.in_interface = String::from("lo");
+        assert!(!rule.matches_packet(&meta2));
+
+        let rule = FirewallRule::new(
+            2,
+            MatchCriteria::new().with_out_interface("eth0"),
+            RuleAction::Drop,
+        );
+        assert!(!rule.matches_packet(&meta));
+    }
+
     #[test]
     fn test_rule_engine_add_evaluate() {
         let mut engine = RuleEngine::new();
@@ -12,10 +12,6 @@ use crate::error::KernelError;
 #[cfg(target_arch = "x86_64")]
 const INTEL_VENDOR_ID: u16 = 0x8086;
 #[cfg(target_arch = "x86_64")]
-const E1000_DEVICE_ID: u16 = 0x100E;
-#[cfg(target_arch = "x86_64")]
-const E1000E_DEVICE_ID: u16 = 0x10D3;
-#[cfg(target_arch = "x86_64")]
 const REDHAT_VENDOR_ID: u16 = 0x1AF4;
 #[cfg(target_arch = "x86_64")]
 const VIRTIO_NET_LEGACY_DEVICE_ID: u16 = 0x1000;
@@ -32,6 +28,8 @@ pub fn register_drivers() -> Result<(), KernelError> {
     // Only x86_64 has PCI support
     #[cfg(target_arch = "x86_64")]
     {
+        let mut e1000_devices = alloc::vec::Vec::new();
+
         // Check if PCI is initialized before trying to access it
         if !crate::drivers::pci::is_pci_initialized() {
             println!("[NET-INTEGRATION] PCI bus not initialized, skipping PCI device scan");
@@ -48,39 +46,11 @@ pub fn register_drivers() -> Result<(), KernelError> {
                 );
             }
 
-            // Search for Intel E1000 network cards
-            let e1000_devices = bus.find_devices_by_id(INTEL_VENDOR_ID, E1000_DEVICE_ID);
-            for device in e1000_devices {
-                println!(
-                    "[NET-INTEGRATION] Found E1000 at {:02x}:{:02x}.{}",
-                    device.location.bus, device.location.device, device.location.function
-                );
-
-                // Get BAR0 (MMIO base address)
-                if let Some(bar0) = device.bars.first() {
-                    if let Some(address) = bar0.get_memory_address() {
-                        if try_register_e1000(address).is_ok() {
-                            device_count += 1;
-                        }
-                    }
-                }
-            }
-
-            // Search for Intel E1000E network cards
-            let e1000e_devices = bus.find_devices_by_id(INTEL_VENDOR_ID, E1000E_DEVICE_ID);
-            for device in e1000e_devices {
-                println!(
-                    "[NET-INTEGRATION] Found E1000E at {:02x}:{:02x}.{}",
-                    device.location.bus, device.location.device, device.location.function
-                );
-
-                if let Some(bar0) = device.bars.first() {
-                    if let Some(address) = bar0.get_memory_address() {
-                        if try_register_e1000(address).is_ok() {
-                            device_count += 1;
-                        }
-                    }
-                }
+            // Search for Intel E1000/E1000E network cards; they are probed
+            // once the bus lock is released, since probing enables bus
+            // mastering through it.
+            for device_id in crate::drivers::e1000::device_ids() {
+                e1000_devices.extend(bus.find_devices_by_id(INTEL_VENDOR_ID, device_id));
             }
 
             // Search for VirtIO-Net (legacy and modern)
@@ -104,19 +74,26 @@ pub fn register_drivers() -> Result<(), KernelError> {
                 }
             }
         }
+
+        for device in e1000_devices {
+            println!(
+                "[NET-INTEGRATION] Found E1000 ({:04x}) at {:02x}:{:02x}.{}",
+                device.device_id,
+                device.location.bus,
+                device.location.device,
+                device.location.function
+            );
+            if try_register_e1000(&device).is_ok() {
+                device_count += 1;
+            }
+        }
     }
 
-    // For non-x86_64 architectures, try VirtIO MMIO at known addresses
+    // For non-x86_64 architectures, try the VirtIO MMIO devices from the
+    // device tree (or the QEMU virt defaults)
     #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
     {
-        // VirtIO MMIO devices at platform-specific addresses
-        #[cfg(target_arch = "aarch64")]
-        let virtio_bases = [0x0a000000, 0x0a000200, 0x0a000400, 0x0a000600];
-
-        #[cfg(target_arch = "riscv64")]
-        let virtio_bases = [0x10001000, 0x10002000, 0x10003000, 0x10004000];
-
-        for &base in &virtio_bases {
+        for base in crate::drivers::virtio::mmio::device_bases() {
             if try_register_virtio_net(base as u64).is_ok() {
                 device_count += 1;
             }
@@ -134,48 +111,19 @@ pub fn register_drivers() -> Result<(), KernelError> {
 ///
 /// Called from the x86_64 PCI device scan path above.
 #[cfg(target_arch = "x86_64")]
-fn try_register_e1000(bar_address: u64) -> Result<(), KernelError> {
-    use crate::drivers::e1000::E1000Driver;
-
-    // The BAR address is physical — convert to virtual via the bootloader's
-    // physical memory offset mapping.
-    let virt_addr = crate::arch::x86_64::msr::phys_to_virt(bar_address as usize).ok_or(
-        KernelError::NotFound {
-            resource: "e1000_mmio_mapping",
-            id: 0,
-        },
-    )?;
-
-    println!(
-        "[NET-INTEGRATION] Initializing E1000 at phys 0x{:x} (virt 0x{:x})",
-        bar_address, virt_addr
-    );
-
-    match E1000Driver::new(virt_addr) {
-        Ok(driver) => {
-            let name = driver.name();
-            let mac = driver.mac_address();
-
+fn try_register_e1000(device: &crate::drivers::pci::PciDevice) -> Result<(), KernelError> {
+    match crate::drivers::e1000::probe(device) {
+        Ok(()) => Ok(()),
+        Err(_e) => {
             println!(
-                "[NET-INTEGRATION] E1000 initialized: {} (MAC: \
-                 {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x})",
-                name, mac.0[0], mac.0[1], mac.0[2], mac.0[3], mac.0[4], mac.0[5]
+                "[NET-INTEGRATION] Warning: failed to initialize E1000: {:?}",
+                _e
             );
-
-            // Register with network device registry
-            if let Err(e) = device::register_device(Box::new(driver)) {
-                println!(
-                    "[NET-INTEGRATION] Warning: failed to register E1000: {:?}",
-                    e
-                );
-            }
-
-            Ok(())
+            Err(KernelError::NotFound {
+                resource: "e1000_hardware",
+                id: 0,
+            })
         }
-        Err(_) => Err(KernelError::NotFound {
-            resource: "e1000_hardware",
-            id: 0,
-        }),
     }
 }
 
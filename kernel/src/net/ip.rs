@@ -3,7 +3,7 @@
 //! Handles IPv4 packet construction, parsing, routing, and fragmentation.
 //! Provides the foundation for TCP and UDP transport protocols.
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
 use spin::Mutex;
 
@@ -168,6 +168,8 @@ pub fn set_interface_config(ip: Ipv4Address, mask: Ipv4Address, gw: Option<Ipv4A
     config.ip_addr = ip;
     config.subnet_mask = mask;
     config.gateway = gw;
+    drop(config);
+    record_address(PRIMARY_INTERFACE, ip, mask);
 
     println!(
         "[IP] Interface configured: {}.{}.{}.{}/{}.{}.{}.{}",
@@ -182,6 +184,69 @@ pub fn set_interface_config(ip: Ipv4Address, mask: Ipv4Address, gw: Option<Ipv4A
     }
 }
 
+/// Interface that IPv4 traffic leaves by, whose address is the one in
+/// [`get_interface_config`]
+pub const PRIMARY_INTERFACE: &str = "eth0";
+
+/// IPv4 address assigned to an interface
+#[derive(Debug, Clone)]
+pub struct InterfaceAddress {
+    pub interface: String,
+    pub addr: Ipv4Address,
+    pub netmask: Ipv4Address,
+}
+
+/// Addresses of all interfaces (at most one IPv4 address each)
+static ADDRESSES: Mutex<Vec<InterfaceAddress>> = Mutex::new(Vec::new());
+
+fn record_address(interface: &str, addr: Ipv4Address, netmask: Ipv4Address) {
+    let mut addresses = ADDRESSES.lock();
+    addresses.retain(|a| a.interface != interface);
+    if addr != Ipv4Address::ANY {
+        addresses.push(InterfaceAddress {
+            interface: String::from(interface),
+            addr,
+            netmask,
+        });
+    }
+}
+
+/// Assign `addr`/`netmask` to `interface`, replacing its address;
+/// 0.0.0.0 removes it. The primary interface keeps its gateway.
+pub fn set_interface_address(interface: &str, addr: Ipv4Address, netmask: Ipv4Address) {
+    if interface == PRIMARY_INTERFACE {
+        let gateway = get_interface_config().gateway;
+        set_interface_config(addr, netmask, gateway);
+    } else {
+        record_address(interface, addr, netmask);
+    }
+}
+
+/// The IPv4 address and netmask of `interface`.
+pub fn interface_address(interface: &str) -> Option<(Ipv4Address, Ipv4Address)> {
+    ADDRESSES
+        .lock()
+        .iter()
+        .find(|a| a.interface == interface)
+        .map(|a| (a.addr, a.netmask))
+}
+
+/// All assigned IPv4 addresses.
+pub fn interface_addresses() -> Vec<InterfaceAddress> {
+    ADDRESSES.lock().clone()
+}
+
+/// Whether `addr` is in 127.0.0.0/8.
+pub fn is_loopback(addr: Ipv4Address) -> bool {
+    addr.0[0] == 127
+}
+
+/// Whether traffic to `addr` stays on this host: loopback addresses and
+/// the addresses of our own interfaces.
+pub fn is_local_address(addr: Ipv4Address) -> bool {
+    is_loopback(addr) || ADDRESSES.lock().iter().any(|a| a.addr == addr)
+}
+
 /// Simple routing table protected by Mutex
 static ROUTES: Mutex<Vec<RouteEntry>> = Mutex::new(Vec::new());
 
@@ -219,8 +284,20 @@ static IP_ID_COUNTER: core::sync::atomic::AtomicU16 = core::sync::atomic::Atomic
 pub fn send(dest: IpAddress, protocol: IpProtocol, data: &[u8]) -> Result<(), KernelError> {
     match dest {
         IpAddress::V4(dest_v4) => {
-            // Use configured interface address (falls back to 0.0.0.0 pre-DHCP)
-            let src = get_interface_ip();
+            // Local destinations go through the loopback interface, from
+            // the address they were sent to. Otherwise use the configured
+            // interface address (falls back to 0.0.0.0 pre-DHCP).
+            let local = is_local_address(dest_v4);
+            let (src, interface) = if local {
+                let src = if is_loopback(dest_v4) {
+                    Ipv4Address::LOCALHOST
+                } else {
+                    dest_v4
+                };
+                (src, super::device::LOOPBACK)
+            } else {
+                (get_interface_ip(), PRIMARY_INTERFACE)
+            };
 
             let mut header = Ipv4Header::new(src, dest_v4, protocol);
             header.total_length = (Ipv4Header::MIN_SIZE + data.len()) as u16;
@@ -229,14 +306,27 @@ pub fn send(dest: IpAddress, protocol: IpProtocol, data: &[u8]) -> Result<(), Ke
             header.flags = 0x02; // Don't Fragment
             header.calculate_checksum();
 
+            super::firewall::hooks::output(interface, &header, data)?;
+
             // Combine IP header + payload
             let header_bytes = header.to_bytes();
             let mut ip_packet = Vec::with_capacity(header_bytes.len() + data.len());
             ip_packet.extend_from_slice(&header_bytes);
             ip_packet.extend_from_slice(data);
 
+            if let Some(mtu) = super::device::mtu(interface) {
+                if ip_packet.len() > mtu {
+                    return Err(KernelError::InvalidArgument {
+                        name: "packet_size",
+                        value: "exceeds interface MTU",
+                    });
+                }
+            }
+
             // Resolve destination MAC via ARP (or use broadcast for broadcast IP)
-            let dst_mac = if dest_v4 == Ipv4Address::BROADCAST {
+            let dst_mac = if local {
+                super::MacAddress::ZERO
+            } else if dest_v4 == Ipv4Address::BROADCAST {
                 super::MacAddress::BROADCAST
             } else {
                 // Check ARP cache; if miss, send ARP request and use broadcast
@@ -246,7 +336,7 @@ pub fn send(dest: IpAddress, protocol: IpProtocol, data: &[u8]) -> Result<(), Ke
                 })
             };
 
-            let src_mac = super::device::with_device("eth0", |dev| dev.mac_address())
+            let src_mac = super::device::with_device(interface, |dev| dev.mac_address())
                 .unwrap_or(super::MacAddress::ZERO);
 
             // Wrap in Ethernet frame
@@ -259,7 +349,7 @@ pub fn send(dest: IpAddress, protocol: IpProtocol, data: &[u8]) -> Result<(), Ke
 
             // Transmit
             let pkt = super::Packet::from_bytes(&frame);
-            super::device::with_device_mut("eth0", |dev| {
+            super::device::with_device_mut(interface, |dev| {
                 let _ = dev.transmit(&pkt);
             });
 
@@ -292,6 +382,11 @@ pub fn init() -> Result<(), KernelError> {
         gateway: None,
         interface: 0,
     });
+    record_address(
+        super::device::LOOPBACK,
+        Ipv4Address::LOCALHOST,
+        Ipv4Address::new(255, 0, 0, 0),
+    );
 
     println!("[IP] IP layer initialized");
     Ok(())
@@ -301,6 +396,24 @@ pub fn init() -> Result<(), KernelError> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_local_addresses() {
+        assert!(is_loopback(Ipv4Address::LOCALHOST));
+        assert!(is_local_address(Ipv4Address::new(127, 1, 2, 3)));
+        assert!(!is_local_address(Ipv4Address::new(10, 99, 0, 1)));
+
+        set_interface_address("test0", Ipv4Address::new(10, 99, 0, 1), Ipv4Address::ANY);
+        assert!(is_local_address(Ipv4Address::new(10, 99, 0, 1)));
+        assert_eq!(
+            interface_address("test0").map(|(addr, _)| addr),
+            Some(Ipv4Address::new(10, 99, 0, 1))
+        );
+
+        set_interface_address("test0", Ipv4Address::ANY, Ipv4Address::ANY);
+        assert!(!is_local_address(Ipv4Address::new(10, 99, 0, 1)));
+        assert_eq!(interface_address("test0"), None);
+    }
+
     #[test]
     fn test_ipv4_header() {
         let src = Ipv4Address::new(192, 168, 1, 1);
@@ -20,6 +20,7 @@ pub mod ip;
 pub mod ipv6;
 pub mod kerberos;
 pub mod ldap;
+pub mod packet_ring;
 pub mod socket;
 pub mod tcp;
 pub mod udp;
@@ -249,6 +250,9 @@ pub fn init() -> Result<(), KernelError> {
     // Initialize ICMPv6
     icmpv6::init()?;
 
+    // Initialize the packet filter (accepts everything until rules are added)
+    firewall::init()?;
+
     // Initialize socket layer
     socket::init()?;
 
@@ -267,6 +271,15 @@ pub fn init() -> Result<(), KernelError> {
     Ok(())
 }
 
+/// Deliver received frames to the stack: loopback traffic and frames the
+/// NIC drivers queued from interrupt context. Also sends what user-space
+/// packet rings queued. Called from the idle loop.
+pub fn poll() {
+    packet_ring::poll();
+    device::poll_loopback();
+    crate::drivers::e1000::poll();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,584 @@
+//! Kernel-bypass packet rings
+//!
+//! A process holding a capability for a network interface can attach a set
+//! of RX/TX rings to it and exchange raw Ethernet frames with the driver
+//! without going through the in-kernel stack, in the style of AF_PACKET's
+//! `PACKET_MMAP` and AF_XDP. This is the data path for user-space network
+//! servers (and a future user-space TCP stack).
+//!
+//! A ring set is one physically contiguous region mapped into both the
+//! kernel and the owning process:
+//!
+//! ```text
+//! 0                 RingHeader (64 bytes)
+//! RX_DESC_OFFSET    RX descriptors, MAX_SLOTS x SlotDesc
+//! TX_DESC_OFFSET    TX descriptors, MAX_SLOTS x SlotDesc
+//! FRAMES_OFFSET     rx_slots RX frame slots, then tx_slots TX frame slots
+//! ```
+//!
+//! Both rings are single-producer/single-consumer with free-running `u32`
+//! indices, and the slot of index `i` is `i % slots`. The kernel produces
+//! RX frames and the process consumes them; the process produces TX frames
+//! and the kernel consumes them when the process kicks the ring or the
+//! network poll loop runs.
+//!
+//! With [`RING_DIVERT`] set, received frames that match the ring's
+//! EtherType filter are taken away from the in-kernel stack; otherwise the
+//! ring sees a copy of them alongside the stack.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use spin::Mutex;
+
+use crate::{
+    cap::{manager::cap_manager, ObjectRef, Rights},
+    error::KernelError,
+    mm::{phys_to_virt_addr, FrameNumber, FRAME_ALLOCATOR, FRAME_SIZE},
+    process::ProcessId,
+};
+
+/// `RingHeader::magic`: "VRNG"
+pub const RING_MAGIC: u32 = 0x5652_4E47;
+
+/// Layout version in `RingHeader::version`
+pub const RING_VERSION: u32 = 1;
+
+/// Bytes per frame slot (one MTU-sized Ethernet frame plus headroom)
+pub const SLOT_SIZE: usize = 2048;
+
+/// Most slots a single ring may have
+pub const MAX_SLOTS: u32 = 256;
+
+/// Offset of the RX descriptor array
+pub const RX_DESC_OFFSET: usize = core::mem::size_of::<RingHeader>();
+
+/// Offset of the TX descriptor array
+pub const TX_DESC_OFFSET: usize =
+    RX_DESC_OFFSET + MAX_SLOTS as usize * core::mem::size_of::<SlotDesc>();
+
+/// Offset of the first frame slot
+pub const FRAMES_OFFSET: usize = FRAME_SIZE;
+
+/// Take matching frames away from the in-kernel stack.
+pub const RING_DIVERT: u32 = 0x1;
+
+/// Object a process must hold a capability for to attach rings to the
+/// interface with index `index`.
+pub fn interface_object(index: u32) -> ObjectRef {
+    // "NET\0" in the top half keeps these IDs apart from bus device IDs
+    ObjectRef::Device {
+        device_id: 0x4E45_5400_0000_0000 | index as u64,
+    }
+}
+
+/// Shared header at the start of a ring set.
+///
+/// The process only writes `rx_consumer` and `tx_producer`; everything
+/// else is written by the kernel.
+#[repr(C)]
+pub struct RingHeader {
+    pub magic: u32,
+    pub version: u32,
+    pub rx_slots: u32,
+    pub tx_slots: u32,
+    pub slot_size: u32,
+    pub flags: u32,
+    pub rx_producer: AtomicU32,
+    pub rx_consumer: AtomicU32,
+    pub tx_producer: AtomicU32,
+    pub tx_consumer: AtomicU32,
+    /// Frames lost because the RX ring was full
+    pub rx_dropped: AtomicU32,
+    /// TX descriptors the kernel rejected (bad length, device error)
+    pub tx_errors: AtomicU32,
+    _reserved: [u32; 4],
+}
+
+/// Per-slot descriptor: length of the frame in the slot.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlotDesc {
+    pub len: u16,
+    pub flags: u16,
+}
+
+const _: () = assert!(core::mem::size_of::<RingHeader>() == 64);
+const _: () = assert!(
+    TX_DESC_OFFSET + MAX_SLOTS as usize * core::mem::size_of::<SlotDesc>() <= FRAMES_OFFSET
+);
+
+/// Bytes needed for a ring set with the given slot counts, page aligned.
+pub fn region_size(rx_slots: u32, tx_slots: u32) -> usize {
+    let size = FRAMES_OFFSET + (rx_slots + tx_slots) as usize * SLOT_SIZE;
+    size.div_ceil(FRAME_SIZE) * FRAME_SIZE
+}
+
+/// View of a ring set's memory.
+///
+/// Only the index owned by the kernel side is ever trusted; the ones the
+/// process writes are validated on every use, since the process can change
+/// them at any time.
+struct RingMem {
+    base: usize,
+    rx_slots: u32,
+    tx_slots: u32,
+}
+
+impl RingMem {
+    /// Lay out a fresh ring set at `base`, which must be zeroed and
+    /// `region_size(rx_slots, tx_slots)` bytes long.
+    fn init(base: usize, rx_slots: u32, tx_slots: u32, flags: u32) -> Self {
+        let mem = Self {
+            base,
+            rx_slots,
+            tx_slots,
+        };
+        // SAFETY: base is the start of a zeroed region at least FRAME_SIZE
+        // bytes long that nothing else references yet, and RingHeader is
+        // repr(C) with only integer fields.
+        let header = unsafe { &mut *(base as *mut RingHeader) };
+        header.magic = RING_MAGIC;
+        header.version = RING_VERSION;
+        header.rx_slots = rx_slots;
+        header.tx_slots = tx_slots;
+        header.slot_size = SLOT_SIZE as u32;
+        header.flags = flags;
+        mem
+    }
+
+    fn header(&self) -> &RingHeader {
+        // SAFETY: base points to the RingHeader written by init(), which
+        // lives as long as the ring. The fields the process may change
+        // concurrently are atomics.
+        unsafe { &*(self.base as *const RingHeader) }
+    }
+
+    fn desc(&self, offset: usize, slot: u32) -> *mut SlotDesc {
+        (self.base + offset + slot as usize * core::mem::size_of::<SlotDesc>()) as *mut SlotDesc
+    }
+
+    fn slot(&self, index: u32) -> *mut u8 {
+        (self.base + FRAMES_OFFSET + index as usize * SLOT_SIZE) as *mut u8
+    }
+
+    /// Frames the process has not consumed yet.
+    fn rx_pending(&self) -> u32 {
+        let header = self.header();
+        let pending = header
+            .rx_producer
+            .load(Ordering::Relaxed)
+            .wrapping_sub(header.rx_consumer.load(Ordering::Acquire));
+        pending.min(self.rx_slots)
+    }
+
+    /// Copy `frame` into the next RX slot. Returns false if the ring is
+    /// full.
+    fn push_rx(&self, frame: &[u8]) -> bool {
+        let header = self.header();
+        let producer = header.rx_producer.load(Ordering::Relaxed);
+        let consumer = header.rx_consumer.load(Ordering::Acquire);
+        // A consumer index ahead of the producer is garbage from the
+        // process; treating the ring as full keeps the kernel's slots safe.
+        if producer.wrapping_sub(consumer) >= self.rx_slots {
+            header.rx_dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        let slot = producer % self.rx_slots;
+        let len = frame.len().min(SLOT_SIZE);
+        // SAFETY: slot < rx_slots, so the slot and its descriptor lie inside
+        // the region. The process does not touch slots between consumer and
+        // producer, and the write is published by the Release store below.
+        unsafe {
+            core::ptr::copy_nonoverlapping(frame.as_ptr(), self.slot(slot), len);
+            self.desc(RX_DESC_OFFSET, slot).write_volatile(SlotDesc {
+                len: len as u16,
+                flags: 0,
+            });
+        }
+        header
+            .rx_producer
+            .store(producer.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// Take the next frame the process queued for transmission.
+    fn pop_tx(&self) -> Option<Vec<u8>> {
+        let header = self.header();
+        loop {
+            let consumer = header.tx_consumer.load(Ordering::Relaxed);
+            let producer = header.tx_producer.load(Ordering::Acquire);
+            let queued = producer.wrapping_sub(consumer);
+            if queued == 0 {
+                return None;
+            }
+            if queued > self.tx_slots {
+                // The producer index is garbage: drop what was queued.
+                header.tx_errors.fetch_add(1, Ordering::Relaxed);
+                header.tx_consumer.store(producer, Ordering::Release);
+                return None;
+            }
+
+            let slot = consumer % self.tx_slots;
+            // SAFETY: slot < tx_slots, so descriptor and slot lie inside the
+            // region; the process may rewrite them at any moment, so they
+            // are read once and the length clamped before copying out.
+            let frame = unsafe {
+                let desc = self.desc(TX_DESC_OFFSET, slot).read_volatile();
+                let len = desc.len as usize;
+                (len > 0 && len <= SLOT_SIZE).then(|| {
+                    core::slice::from_raw_parts(self.slot(self.rx_slots + slot), len).to_vec()
+                })
+            };
+            header
+                .tx_consumer
+                .store(consumer.wrapping_add(1), Ordering::Release);
+            match frame {
+                Some(frame) => return Some(frame),
+                None => {
+                    header.tx_errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+/// A ring set attached to an interface.
+pub struct PacketRing {
+    owner: ProcessId,
+    interface: String,
+    flags: u32,
+    /// Only frames with this EtherType are delivered (0 = all)
+    ethertype: u16,
+    first_frame: FrameNumber,
+    frame_count: usize,
+    /// Address of the ring set in the owner's address space
+    user_addr: usize,
+    mem: RingMem,
+}
+
+impl PacketRing {
+    /// Physical address of the ring set.
+    pub fn phys_addr(&self) -> u64 {
+        self.first_frame.as_u64() * FRAME_SIZE as u64
+    }
+
+    /// Size of the ring set in bytes.
+    pub fn size(&self) -> usize {
+        self.frame_count * FRAME_SIZE
+    }
+
+    /// Address of the ring set in the owner's address space.
+    pub fn user_addr(&self) -> usize {
+        self.user_addr
+    }
+
+    fn matches(&self, interface: &str, frame: &[u8]) -> bool {
+        self.interface == interface
+            && (self.ethertype == 0
+                || (frame.len() >= 14
+                    && u16::from_be_bytes([frame[12], frame[13]]) == self.ethertype))
+    }
+
+    /// Transmit everything the process queued. Returns the frame count.
+    fn transmit(&self) -> usize {
+        let mut sent = 0;
+        while let Some(frame) = self.mem.pop_tx() {
+            let packet = super::Packet::from_bytes(&frame);
+            match super::device::with_device_mut(&self.interface, |dev| dev.transmit(&packet)) {
+                Some(Ok(())) => {
+                    super::update_stats_tx(frame.len());
+                    sent += 1;
+                }
+                _ => {
+                    self.mem.header().tx_errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        sent
+    }
+
+    fn free(self) {
+        let _ = FRAME_ALLOCATOR
+            .lock()
+            .free_frames(self.first_frame, self.frame_count);
+    }
+}
+
+/// Attached ring sets by ID.
+static RINGS: Mutex<BTreeMap<u32, PacketRing>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+fn not_found(id: u32) -> KernelError {
+    KernelError::NotFound {
+        resource: "packet ring",
+        id: id as u64,
+    }
+}
+
+/// Whether process `pid` holds a read/write capability for interface
+/// `index`.
+pub fn may_attach(pid: ProcessId, index: u32) -> bool {
+    let Some(process) = crate::process::get_process(pid) else {
+        return false;
+    };
+    let object = interface_object(index);
+    let mut found = false;
+    let cap_space = process.capability_space.lock();
+    let _ = cap_space.iter_capabilities(|entry| {
+        found = entry.object == object
+            && entry.rights.contains(Rights::READ | Rights::WRITE)
+            && cap_manager().is_valid(entry.capability);
+        !found
+    });
+    found
+}
+
+/// Give process `pid` a capability for interface `index`.
+pub fn grant(pid: ProcessId, index: u32) -> Result<(), KernelError> {
+    if super::device::interface_by_index(index).is_none() {
+        return Err(KernelError::NotFound {
+            resource: "network interface",
+            id: index as u64,
+        });
+    }
+    let process =
+        crate::process::get_process(pid).ok_or(KernelError::ProcessNotFound { pid: pid.0 })?;
+    let cap_space = process.capability_space.lock();
+    cap_manager()
+        .create_capability(
+            interface_object(index),
+            Rights::READ | Rights::WRITE,
+            &cap_space,
+        )
+        .map_err(|_| KernelError::ResourceExhausted {
+            resource: "capabilities",
+        })?;
+    Ok(())
+}
+
+/// Attach a ring set to interface `index` on behalf of `owner`.
+///
+/// Slot counts must be powers of two no larger than [`MAX_SLOTS`]. The
+/// caller maps the region at [`PacketRing::phys_addr`] into the owner and
+/// records where with [`set_user_addr`]. Returns the ring ID.
+pub fn attach(
+    owner: ProcessId,
+    index: u32,
+    rx_slots: u32,
+    tx_slots: u32,
+    flags: u32,
+    ethertype: u16,
+) -> Result<u32, KernelError> {
+    let valid = |n: u32| n.is_power_of_two() && n <= MAX_SLOTS;
+    if !valid(rx_slots) || !valid(tx_slots) {
+        return Err(KernelError::InvalidArgument {
+            name: "slots",
+            value: "not a power of two up to MAX_SLOTS",
+        });
+    }
+    if flags & !RING_DIVERT != 0 {
+        return Err(KernelError::InvalidArgument {
+            name: "flags",
+            value: "unknown flag",
+        });
+    }
+    let info = super::device::interface_by_index(index).ok_or(KernelError::NotFound {
+        resource: "network interface",
+        id: index as u64,
+    })?;
+    if !may_attach(owner, index) {
+        return Err(KernelError::PermissionDenied {
+            operation: "attach packet ring",
+        });
+    }
+
+    let frame_count = region_size(rx_slots, tx_slots) / FRAME_SIZE;
+    let first_frame = FRAME_ALLOCATOR
+        .lock()
+        .allocate_frames(frame_count, None)
+        .map_err(|_| KernelError::OutOfMemory {
+            requested: frame_count * FRAME_SIZE,
+            available: 0,
+        })?;
+    let base = phys_to_virt_addr(first_frame.as_u64() * FRAME_SIZE as u64) as usize;
+    // SAFETY: the frames were just allocated for this ring and are mapped
+    // in the kernel's physical memory window. Zeroing them keeps stale
+    // kernel data from reaching the process.
+    unsafe { core::ptr::write_bytes(base as *mut u8, 0, frame_count * FRAME_SIZE) };
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    RINGS.lock().insert(
+        id,
+        PacketRing {
+            owner,
+            interface: info.name,
+            flags,
+            ethertype,
+            first_frame,
+            frame_count,
+            user_addr: 0,
+            mem: RingMem::init(base, rx_slots, tx_slots, flags),
+        },
+    );
+    Ok(id)
+}
+
+/// Run `f` on ring `id` of `owner`.
+pub fn with_ring<R>(
+    owner: ProcessId,
+    id: u32,
+    f: impl FnOnce(&mut PacketRing) -> R,
+) -> Result<R, KernelError> {
+    match RINGS.lock().get_mut(&id) {
+        Some(ring) if ring.owner == owner => Ok(f(ring)),
+        _ => Err(not_found(id)),
+    }
+}
+
+/// Record where ring `id` is mapped in its owner.
+pub fn set_user_addr(owner: ProcessId, id: u32, addr: usize) -> Result<(), KernelError> {
+    with_ring(owner, id, |ring| ring.user_addr = addr)
+}
+
+/// Detach ring `id` of `owner` and free it. The caller must already have
+/// unmapped it from the owner.
+pub fn detach(owner: ProcessId, id: u32) -> Result<(), KernelError> {
+    let mut rings = RINGS.lock();
+    match rings.get(&id) {
+        Some(ring) if ring.owner == owner => {}
+        _ => return Err(not_found(id)),
+    }
+    if let Some(ring) = rings.remove(&id) {
+        ring.free();
+    }
+    Ok(())
+}
+
+/// Transmit the frames queued on ring `id`. Returns how many were sent.
+pub fn kick(owner: ProcessId, id: u32) -> Result<usize, KernelError> {
+    with_ring(owner, id, |ring| ring.transmit())
+}
+
+/// Number of received frames waiting on ring `id`.
+pub fn rx_pending(owner: ProcessId, id: u32) -> Result<u32, KernelError> {
+    with_ring(owner, id, |ring| ring.mem.rx_pending())
+}
+
+/// Offer a frame received on `interface` to the attached rings.
+///
+/// Every matching ring gets a copy. Returns true if a diverting ring took
+/// the frame, in which case the stack must not process it.
+pub fn deliver(interface: &str, frame: &[u8]) -> bool {
+    let rings = RINGS.lock();
+    let mut diverted = false;
+    for ring in rings.values().filter(|r| r.matches(interface, frame)) {
+        ring.mem.push_rx(frame);
+        diverted |= ring.flags & RING_DIVERT != 0;
+    }
+    diverted
+}
+
+/// Transmit what processes queued without kicking. Called from the network
+/// poll loop.
+pub fn poll() {
+    let rings = match RINGS.try_lock() {
+        Some(rings) => rings,
+        None => return,
+    };
+    for ring in rings.values() {
+        ring.transmit();
+    }
+}
+
+/// Free the rings of an exiting process.
+pub fn release_process(pid: ProcessId) {
+    let mut rings = RINGS.lock();
+    let ids: Vec<u32> = rings
+        .iter()
+        .filter(|(_, r)| r.owner == pid)
+        .map(|(&id, _)| id)
+        .collect();
+    for id in ids {
+        if let Some(ring) = rings.remove(&id) {
+            ring.free();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn region(rx_slots: u32, tx_slots: u32) -> Vec<u64> {
+        vec![0u64; region_size(rx_slots, tx_slots) / 8]
+    }
+
+    #[test]
+    fn test_region_size() {
+        assert_eq!(region_size(1, 1), FRAME_SIZE * 2);
+        assert_eq!(region_size(2, 2), FRAME_SIZE * 3);
+        assert_eq!(
+            region_size(MAX_SLOTS, MAX_SLOTS),
+            FRAMES_OFFSET + 2 * MAX_SLOTS as usize * SLOT_SIZE
+        );
+    }
+
+    #[test]
+    fn test_rx_fills_and_drains() {
+        let mut buf = region(2, 2);
+        let mem = RingMem::init(buf.as_mut_ptr() as usize, 2, 2, 0);
+        assert_eq!(mem.header().magic, RING_MAGIC);
+
+        assert!(mem.push_rx(&[1, 2, 3]));
+        assert!(mem.push_rx(&[4]));
+        assert!(!mem.push_rx(&[5]));
+        assert_eq!(mem.rx_pending(), 2);
+        assert_eq!(mem.header().rx_dropped.load(Ordering::Relaxed), 1);
+
+        // SAFETY: slot 0 and its descriptor lie inside `buf`.
+        let (desc, byte) = unsafe { (mem.desc(RX_DESC_OFFSET, 0).read(), *mem.slot(0).add(2)) };
+        assert_eq!(desc.len, 3);
+        assert_eq!(byte, 3);
+
+        mem.header().rx_consumer.store(2, Ordering::Release);
+        assert_eq!(mem.rx_pending(), 0);
+        assert!(mem.push_rx(&[6]));
+    }
+
+    #[test]
+    fn test_tx_pops_and_rejects_bad_descriptors() {
+        let mut buf = region(1, 2);
+        let mem = RingMem::init(buf.as_mut_ptr() as usize, 1, 2, 0);
+        // SAFETY: TX slot 0 (frame slot 1) and both descriptors lie inside
+        // `buf`.
+        unsafe {
+            mem.slot(1).write(0xAB);
+            mem.desc(TX_DESC_OFFSET, 0)
+                .write(SlotDesc { len: 1, flags: 0 });
+            mem.desc(TX_DESC_OFFSET, 1).write(SlotDesc {
+                len: SLOT_SIZE as u16 + 1,
+                flags: 0,
+            });
+        }
+        mem.header().tx_producer.store(2, Ordering::Release);
+
+        assert_eq!(mem.pop_tx(), Some(vec![0xAB]));
+        assert_eq!(mem.pop_tx(), None);
+        assert_eq!(mem.header().tx_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(mem.header().tx_consumer.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_tx_garbage_producer_is_dropped() {
+        let mut buf = region(1, 1);
+        let mem = RingMem::init(buf.as_mut_ptr() as usize, 1, 1, 0);
+        mem.header().tx_producer.store(100, Ordering::Release);
+        assert_eq!(mem.pop_tx(), None);
+        assert_eq!(mem.header().tx_consumer.load(Ordering::Relaxed), 100);
+    }
+}
@@ -70,6 +70,8 @@ pub struct SocketOptions {
     pub send_buffer_size: usize,
     pub recv_timeout_ms: Option<u64>,
     pub send_timeout_ms: Option<u64>,
+    /// TCP_NODELAY: send segments without coalescing small writes
+    pub no_delay: bool,
 }
 
 impl Default for SocketOptions {
@@ -83,6 +85,7 @@ impl Default for SocketOptions {
             send_buffer_size: 65536,
             recv_timeout_ms: None,
             send_timeout_ms: None,
+            no_delay: false,
         }
     }
 }
@@ -291,6 +294,12 @@ impl Socket {
         }
     }
 
+    /// Source address for outgoing datagrams (unspecified until bound)
+    fn datagram_source(&self) -> SocketAddr {
+        self.local_addr
+            .unwrap_or_else(|| SocketAddr::v4(super::Ipv4Address::UNSPECIFIED, 0))
+    }
+
     /// Send data
     pub fn send(&mut self, data: &[u8], _flags: u32) -> Result<usize, KernelError> {
         if self.state != SocketState::Connected {
@@ -325,7 +334,7 @@ impl Socket {
             }
             SocketType::Dgram => {
                 // UDP send
-                super::udp::UdpSocket::new().send_to(data, remote)
+                super::udp::send_datagram(self.datagram_source(), remote, data)
             }
             SocketType::Raw => Err(KernelError::NotImplemented {
                 feature: "raw_socket_send",
@@ -348,7 +357,7 @@ impl Socket {
         }
 
         // Send via UDP
-        super::udp::UdpSocket::new().send_to(data, dest)
+        super::udp::send_datagram(self.datagram_source(), dest, data)
     }
 
     /// Receive data
@@ -441,9 +450,49 @@ impl Socket {
             SocketOption::SendBufferSize(val) => self.options.send_buffer_size = val,
             SocketOption::RecvTimeout(val) => self.options.recv_timeout_ms = val,
             SocketOption::SendTimeout(val) => self.options.send_timeout_ms = val,
+            SocketOption::NoDelay(val) => self.options.no_delay = val,
         }
         Ok(())
     }
+
+    /// Readiness for poll/epoll, as POLL* bits.
+    ///
+    /// Data the TCP layer has queued is pulled into the receive buffer so
+    /// that a following `recv` finds it.
+    pub fn poll_readiness(&mut self) -> u16 {
+        const POLLIN: u16 = 0x0001;
+        const POLLOUT: u16 = 0x0004;
+        const POLLHUP: u16 = 0x0010;
+
+        match (self.state, self.socket_type) {
+            (SocketState::Closed, _) => POLLHUP,
+            (SocketState::Listening, _) => {
+                if has_pending_connection(self.id) {
+                    POLLIN
+                } else {
+                    0
+                }
+            }
+            (SocketState::Connected, SocketType::Stream) => {
+                if self.recv_buffer.is_empty() {
+                    super::tcp::receive_data(self.id, &mut self.recv_buffer);
+                }
+                if self.recv_buffer.is_empty() {
+                    POLLOUT
+                } else {
+                    POLLIN | POLLOUT
+                }
+            }
+            (_, SocketType::Dgram) => {
+                if super::udp::has_datagram(self.id) {
+                    POLLIN | POLLOUT
+                } else {
+                    POLLOUT
+                }
+            }
+            _ => 0,
+        }
+    }
 }
 
 /// Socket option values
@@ -457,6 +506,7 @@ pub enum SocketOption {
     SendBufferSize(usize),
     RecvTimeout(Option<u64>),
     SendTimeout(Option<u64>),
+    NoDelay(bool),
 }
 
 /// Socket table for managing all sockets
@@ -599,6 +649,14 @@ fn dequeue_pending_connection(socket_id: usize) -> Option<PendingConnection> {
     None
 }
 
+/// Whether a listening socket has a connection waiting to be accepted
+fn has_pending_connection(socket_id: usize) -> bool {
+    LISTENING_SOCKETS
+        .lock()
+        .iter()
+        .any(|e| e.socket_id == socket_id && !e.pending_connections.is_empty())
+}
+
 /// Queue a new connection to a listening socket (called by TCP layer)
 pub fn queue_pending_connection(
     addr: SocketAddr,
@@ -627,9 +685,13 @@ pub fn queue_pending_connection(
     })
 }
 
-/// Close a socket by ID
+/// Close a socket by ID and remove it from the socket table
 pub fn close_socket(id: usize) -> Result<(), KernelError> {
-    with_socket_mut(id, |socket| socket.close())?
+    with_socket_mut(id, |socket| socket.close())??;
+    if let Some(ref mut sockets) = *SOCKET_TABLE.lock() {
+        sockets.retain(|s| s.id != id);
+    }
+    Ok(())
 }
 
 /// Summary of a socket's state for display purposes.
@@ -712,26 +774,142 @@ pub fn getpeername(id: usize) -> Result<crate::net::SocketAddr, KernelError> {
 }
 
 /// Set a socket option.
-pub fn setsockopt(
-    _id: usize,
-    _level: i32,
-    _optname: i32,
-    _optval_ptr: usize,
-    _optlen: usize,
-) -> Result<usize, KernelError> {
-    // Stub: accept all options without error
-    Ok(0)
+pub fn setsockopt(id: usize, option: SocketOption) -> Result<(), KernelError> {
+    with_socket_mut(id, |socket| socket.set_option(option))?
 }
 
-/// Get a socket option.
-pub fn getsockopt(
-    _id: usize,
-    _level: i32,
-    _optname: i32,
-    _optval_ptr: usize,
-) -> Result<usize, KernelError> {
-    // Stub: return 0 for all options
-    Ok(0)
+/// Get the options of a socket.
+pub fn getsockopt(id: usize) -> Result<SocketOptions, KernelError> {
+    with_socket(id, |socket| socket.options)
+}
+
+// -----------------------------------------------------------------------
+// VfsNode adapter -- allows sockets to live in the process file table
+// -----------------------------------------------------------------------
+
+use alloc::sync::Arc;
+
+use crate::fs::{DirEntry, Metadata, NodeType, Permissions, VfsNode};
+
+/// The socket a descriptor refers to: an entry in the INET socket table or
+/// a Unix domain socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketHandle {
+    Inet(usize),
+    Unix(u64),
+}
+
+/// VfsNode wrapper around a socket.
+///
+/// `socket()`, `accept()` and `socketpair()` return real file descriptors,
+/// so sockets work with poll/select/epoll, `fcntl(O_NONBLOCK)`, `dup`,
+/// `fork` and `close` like any other file. `read()`/`write()` map to
+/// `recv()`/`send()` without flags. The socket is closed when the last
+/// descriptor referring to it goes away.
+pub struct SocketNode {
+    handle: SocketHandle,
+}
+
+impl SocketNode {
+    pub fn new(handle: SocketHandle) -> Self {
+        Self { handle }
+    }
+
+    /// Get the socket behind this node (for the socket syscalls).
+    pub fn handle(&self) -> SocketHandle {
+        self.handle
+    }
+}
+
+impl VfsNode for SocketNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Socket
+    }
+
+    fn read(&self, _offset: usize, buffer: &mut [u8]) -> Result<usize, KernelError> {
+        match self.handle {
+            SocketHandle::Inet(id) => with_socket_mut(id, |s| s.recv(buffer, 0))?,
+            SocketHandle::Unix(id) => super::unix_socket::socket_recv(id, buffer).map(|r| r.0),
+        }
+    }
+
+    fn write(&self, _offset: usize, data: &[u8]) -> Result<usize, KernelError> {
+        match self.handle {
+            SocketHandle::Inet(id) => with_socket_mut(id, |s| s.send(data, 0))?,
+            SocketHandle::Unix(id) => super::unix_socket::socket_send(id, data, None),
+        }
+    }
+
+    fn poll_readiness(&self) -> u16 {
+        const POLLERR: u16 = 0x0008;
+        match self.handle {
+            SocketHandle::Inet(id) => {
+                with_socket_mut(id, |s| s.poll_readiness()).unwrap_or(POLLERR)
+            }
+            SocketHandle::Unix(id) => super::unix_socket::poll_readiness(id),
+        }
+    }
+
+    fn as_any(&self) -> Option<&dyn core::any::Any> {
+        Some(self)
+    }
+
+    fn metadata(&self) -> Result<Metadata, KernelError> {
+        Ok(Metadata {
+            size: 0,
+            node_type: NodeType::Socket,
+            permissions: Permissions::from_mode(0o777),
+            uid: 0,
+            gid: 0,
+            created: 0,
+            modified: 0,
+            accessed: 0,
+            inode: 0,
+        })
+    }
+
+    fn readdir(&self) -> Result<Vec<DirEntry>, KernelError> {
+        Err(KernelError::FsError(crate::error::FsError::NotADirectory))
+    }
+
+    fn lookup(&self, _name: &str) -> Result<Arc<dyn VfsNode>, KernelError> {
+        Err(KernelError::FsError(crate::error::FsError::NotADirectory))
+    }
+
+    fn create(
+        &self,
+        _name: &str,
+        _permissions: Permissions,
+    ) -> Result<Arc<dyn VfsNode>, KernelError> {
+        Err(KernelError::FsError(crate::error::FsError::NotADirectory))
+    }
+
+    fn mkdir(
+        &self,
+        _name: &str,
+        _permissions: Permissions,
+    ) -> Result<Arc<dyn VfsNode>, KernelError> {
+        Err(KernelError::FsError(crate::error::FsError::NotADirectory))
+    }
+
+    fn unlink(&self, _name: &str) -> Result<(), KernelError> {
+        Err(KernelError::FsError(crate::error::FsError::NotADirectory))
+    }
+
+    fn truncate(&self, _size: usize) -> Result<(), KernelError> {
+        Err(KernelError::PermissionDenied {
+            operation: "truncate socket",
+        })
+    }
+}
+
+impl Drop for SocketNode {
+    fn drop(&mut self) {
+        let _ = match self.handle {
+            SocketHandle::Inet(id) => close_socket(id),
+            SocketHandle::Unix(id) => super::unix_socket::socket_close(id),
+        };
+    }
 }
 
 #[cfg(test)]
@@ -590,23 +590,42 @@ pub fn process_packet(
         }
     }
 
-    // No matching connection -- send RST if the incoming packet is not RST
-    if !flags.has(TcpFlags::RST) {
-        let rst = build_tcp_segment(
-            dst_port,
-            src_port,
-            ack_num,
-            seq_num.wrapping_add(payload.len() as u32),
-            TcpFlags::RST | TcpFlags::ACK,
-            0,
-            &[],
-        );
-        let _ = send_tcp_via_ip(src_addr, &rst);
-    }
+    // No matching connection -- send RST
+    send_reset(src_addr, data);
 
     Ok(())
 }
 
+/// Answer the segment `data` from `src_addr` with a RST, unless it is a
+/// RST itself. Used for segments that match no connection and for
+/// segments the firewall rejects.
+pub fn send_reset(src_addr: super::IpAddress, data: &[u8]) {
+    if data.len() < TCP_HEADER_SIZE {
+        return;
+    }
+    let flags = TcpFlags::new(data[13]);
+    if flags.has(TcpFlags::RST) {
+        return;
+    }
+    let src_port = u16::from_be_bytes([data[0], data[1]]);
+    let dst_port = u16::from_be_bytes([data[2], data[3]]);
+    let seq_num = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+    let ack_num = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
+    let data_offset = ((data[12] >> 4) * 4) as usize;
+    let payload_len = data.len().saturating_sub(data_offset) as u32;
+
+    let rst = build_tcp_segment(
+        dst_port,
+        src_port,
+        ack_num,
+        seq_num.wrapping_add(payload_len),
+        TcpFlags::RST | TcpFlags::ACK,
+        0,
+        &[],
+    );
+    let _ = send_tcp_via_ip(src_addr, &rst);
+}
+
 /// Generate initial sequence number
 fn generate_initial_seq() -> u32 {
     // In real implementation, use secure random + timestamp
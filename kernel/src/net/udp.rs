@@ -132,6 +132,28 @@ impl Drop for UdpSocket {
     }
 }
 
+/// Build a UDP datagram from `local` to `dest` and hand it to the IP layer.
+///
+/// The socket layer owns its own binding, so it calls this directly rather
+/// than going through a [`UdpSocket`].
+pub fn send_datagram(
+    local: SocketAddr,
+    dest: SocketAddr,
+    data: &[u8],
+) -> Result<usize, KernelError> {
+    let mut header = UdpHeader::new(local.port(), dest.port(), data.len());
+    header.calculate_checksum(local.ip(), dest.ip(), data);
+
+    let mut datagram = Vec::with_capacity(UdpHeader::SIZE + data.len());
+    datagram.extend_from_slice(&header.to_bytes());
+    datagram.extend_from_slice(data);
+
+    // Send via IP layer
+    super::ip::send(dest.ip(), super::ip::IpProtocol::Udp, &datagram)?;
+
+    Ok(data.len())
+}
+
 impl UdpSocket {
     /// Bind to local address
     pub fn bind(&mut self, addr: SocketAddr) -> Result<(), KernelError> {
@@ -170,18 +192,7 @@ impl UdpSocket {
             });
         }
 
-        // Create UDP header
-        let src_port = self.local.port();
-        let dst_port = dest.port();
-        let mut header = UdpHeader::new(src_port, dst_port, data.len());
-
-        // Calculate checksum
-        header.calculate_checksum(self.local.ip(), dest.ip(), data);
-
-        // Send via IP layer
-        super::ip::send(dest.ip(), super::ip::IpProtocol::Udp, data)?;
-
-        Ok(data.len())
+        send_datagram(self.local, dest, data)
     }
 
     /// Send data to connected address
@@ -283,6 +294,14 @@ pub fn receive_from(
     })
 }
 
+/// Whether a datagram is waiting on a socket (poll readiness)
+pub fn has_datagram(socket_id: usize) -> bool {
+    UDP_SOCKETS
+        .lock()
+        .get(&socket_id)
+        .is_some_and(|sock_buf| !sock_buf.recv_queue.is_empty())
+}
+
 /// Process incoming UDP packet (called by IP layer)
 pub fn process_packet(
     src_addr: IpAddress,
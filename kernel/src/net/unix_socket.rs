@@ -6,21 +6,38 @@
 //!
 //! Supported features:
 //! - Stream (SOCK_STREAM) and datagram (SOCK_DGRAM) modes
-//! - Path-based binding (`/run/wayland-0`, `/tmp/.X11-unix/X0`)
+//! - Path-based binding (`/run/wayland-0`, `/tmp/.X11-unix/X0`) and the Linux
+//!   abstract namespace (names starting with a NUL byte)
 //! - `socketpair()` for anonymous connected socket pairs
 //! - SCM_RIGHTS for file descriptor passing (Wayland buffer handles)
+//! - Peer credentials (SO_PEERCRED) captured at connect time
 //! - Backlog queue for pending connections
+//!
+//! User space reaches a socket through a file descriptor
+//! ([`SocketNode`](super::socket::SocketNode)); the socket is torn down
+//! once the last descriptor referring to it is closed, whichever process
+//! (or in-flight SCM_RIGHTS message) held it.
+//!
+//! Descriptors passed with SCM_RIGHTS travel as references to the open
+//! files, not as numbers: the sender's table is resolved at send time and
+//! the receiver gets fresh descriptors for the same open files, the way a
+//! capability is delegated rather than copied by value.
 
 use alloc::{
     collections::{BTreeMap, VecDeque},
     string::{String, ToString},
+    sync::Arc,
     vec::Vec,
 };
 use core::sync::atomic::{AtomicU64, Ordering};
 
 use spin::Mutex;
 
-use crate::error::{KernelError, KernelResult};
+use super::socket::{SocketOption, SocketOptions};
+use crate::{
+    error::{KernelError, KernelResult},
+    fs::file::File,
+};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -69,11 +86,27 @@ pub enum UnixSocketState {
     Closed,
 }
 
+/// Process credentials of a socket's creator (`struct ucred`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Credentials {
+    pub pid: u64,
+    pub uid: u32,
+    pub gid: u32,
+}
+
 /// Ancillary data for SCM_RIGHTS file descriptor passing.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ScmRights {
-    /// File descriptor numbers to pass.
-    pub fds: Vec<u32>,
+    /// Open files in flight; installed into the receiver's file table.
+    pub files: Vec<Arc<File>>,
+}
+
+impl core::fmt::Debug for ScmRights {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScmRights")
+            .field("files", &self.files.len())
+            .finish()
+    }
 }
 
 /// A message in the Unix socket buffer.
@@ -85,6 +118,19 @@ pub struct UnixMessage {
     pub rights: Option<ScmRights>,
     /// Sender socket ID (for datagram mode).
     pub sender: u64,
+    /// Path the sender was bound to when it sent, if any.
+    pub sender_path: Option<String>,
+}
+
+/// Result of a receive.
+#[derive(Debug)]
+pub struct Received {
+    /// Bytes copied into the caller's buffer.
+    pub len: usize,
+    /// Descriptors that arrived with the data.
+    pub rights: Option<ScmRights>,
+    /// Sender address (datagram sockets).
+    pub from: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -109,7 +155,8 @@ pub struct UnixSocket {
     pub recv_buffer_max: usize,
     /// Current receive buffer size in bytes.
     pub recv_buffer_used: usize,
-    /// Pending connection queue (for listening sockets).
+    /// Pending connection queue (for listening sockets). Holds the
+    /// server-side sockets that `accept` hands out.
     pub pending_connections: VecDeque<u64>,
     /// Backlog limit for pending connections.
     pub backlog: usize,
@@ -117,12 +164,18 @@ pub struct UnixSocket {
     pub shutdown_read: bool,
     /// Whether the socket has been shut down for writing.
     pub shutdown_write: bool,
-    /// Owning process ID.
-    pub owner_pid: u64,
+    /// The stream peer has gone away; reads drain the buffer, then see EOF.
+    pub peer_closed: bool,
+    /// Creator's credentials.
+    pub owner: Credentials,
+    /// Credentials of the peer, as of connect time.
+    pub peer_cred: Option<Credentials>,
+    /// SOL_SOCKET options (timeouts, buffer sizes).
+    pub options: SocketOptions,
 }
 
 impl UnixSocket {
-    fn new(id: u64, socket_type: UnixSocketType, owner_pid: u64) -> Self {
+    fn new(id: u64, socket_type: UnixSocketType, owner: Credentials) -> Self {
         Self {
             id,
             socket_type,
@@ -136,9 +189,33 @@ impl UnixSocket {
             backlog: 0,
             shutdown_read: false,
             shutdown_write: false,
-            owner_pid,
+            peer_closed: false,
+            owner,
+            peer_cred: None,
+            options: SocketOptions::default(),
         }
     }
+
+    /// Check that `len` more bytes fit in the receive buffer.
+    ///
+    /// Done before the message is built, so that descriptors of a message
+    /// that cannot be queued are not dropped with the registry locked.
+    fn has_room(&self, len: usize) -> KernelResult<()> {
+        if self.shutdown_read {
+            return Err(KernelError::BrokenPipe);
+        }
+        if self.recv_buffer_used + len > self.recv_buffer_max {
+            return Err(KernelError::WouldBlock);
+        }
+        Ok(())
+    }
+
+    fn enqueue(&mut self, msg: UnixMessage) -> usize {
+        let len = msg.data.len();
+        self.recv_buffer_used += len;
+        self.recv_buffer.push_back(msg);
+        len
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -149,11 +226,41 @@ impl UnixSocket {
 static NEXT_SOCKET_ID: AtomicU64 = AtomicU64::new(1);
 
 /// Global registry of all Unix sockets, keyed by socket ID.
+///
+/// Lock order: `UNIX_SOCKETS` before `PATH_REGISTRY`.
 static UNIX_SOCKETS: Mutex<BTreeMap<u64, UnixSocket>> = Mutex::new(BTreeMap::new());
 
 /// Path-to-socket-ID mapping for bound sockets.
 static PATH_REGISTRY: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
 
+fn not_found(id: u64) -> KernelError {
+    KernelError::NotFound {
+        resource: "unix_socket",
+        id,
+    }
+}
+
+fn lookup_path(path: &str) -> KernelResult<u64> {
+    PATH_REGISTRY
+        .lock()
+        .get(path)
+        .copied()
+        .ok_or(KernelError::NotFound {
+            resource: "unix_socket_path",
+            id: 0,
+        })
+}
+
+fn check_rights(rights: &Option<ScmRights>) -> KernelResult<()> {
+    match rights {
+        Some(r) if r.files.len() > SCM_RIGHTS_MAX => Err(KernelError::InvalidArgument {
+            name: "rights",
+            value: "exceeds SCM_RIGHTS_MAX",
+        }),
+        _ => Ok(()),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Socket API
 // ---------------------------------------------------------------------------
@@ -161,19 +268,16 @@ static PATH_REGISTRY: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new())
 /// Create a new Unix domain socket.
 ///
 /// Returns the socket ID.
-pub fn socket_create(socket_type: UnixSocketType, owner_pid: u64) -> KernelResult<u64> {
-    let sockets = UNIX_SOCKETS.lock();
+pub fn socket_create(socket_type: UnixSocketType, owner: Credentials) -> KernelResult<u64> {
+    let mut sockets = UNIX_SOCKETS.lock();
     if sockets.len() >= UNIX_SOCKET_MAX {
         return Err(KernelError::ResourceExhausted {
             resource: "unix_sockets",
         });
     }
-    drop(sockets);
 
     let id = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
-    let socket = UnixSocket::new(id, socket_type, owner_pid);
-
-    UNIX_SOCKETS.lock().insert(id, socket);
+    sockets.insert(id, UnixSocket::new(id, socket_type, owner));
     Ok(id)
 }
 
@@ -186,6 +290,16 @@ pub fn socket_bind(socket_id: u64, path: &str) -> KernelResult<()> {
         });
     }
 
+    let mut sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get_mut(&socket_id).ok_or(not_found(socket_id))?;
+
+    if socket.path.is_some() || socket.state == UnixSocketState::Listening {
+        return Err(KernelError::InvalidState {
+            expected: "unbound",
+            actual: "already bound",
+        });
+    }
+
     let mut paths = PATH_REGISTRY.lock();
     if paths.contains_key(path) {
         return Err(KernelError::AlreadyExists {
@@ -194,21 +308,10 @@ pub fn socket_bind(socket_id: u64, path: &str) -> KernelResult<()> {
         });
     }
 
-    let mut sockets = UNIX_SOCKETS.lock();
-    let socket = sockets.get_mut(&socket_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: socket_id,
-    })?;
-
-    if socket.state != UnixSocketState::Unbound {
-        return Err(KernelError::InvalidState {
-            expected: "unbound",
-            actual: "already bound or connected",
-        });
-    }
-
     socket.path = Some(path.to_string());
-    socket.state = UnixSocketState::Bound;
+    if socket.state == UnixSocketState::Unbound {
+        socket.state = UnixSocketState::Bound;
+    }
     paths.insert(path.to_string(), socket_id);
 
     Ok(())
@@ -217,10 +320,7 @@ pub fn socket_bind(socket_id: u64, path: &str) -> KernelResult<()> {
 /// Start listening for incoming connections (stream sockets only).
 pub fn socket_listen(socket_id: u64, backlog: usize) -> KernelResult<()> {
     let mut sockets = UNIX_SOCKETS.lock();
-    let socket = sockets.get_mut(&socket_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: socket_id,
-    })?;
+    let socket = sockets.get_mut(&socket_id).ok_or(not_found(socket_id))?;
 
     if socket.socket_type != UnixSocketType::Stream {
         return Err(KernelError::InvalidArgument {
@@ -229,39 +329,62 @@ pub fn socket_listen(socket_id: u64, backlog: usize) -> KernelResult<()> {
         });
     }
 
-    if socket.state != UnixSocketState::Bound {
+    if !matches!(
+        socket.state,
+        UnixSocketState::Bound | UnixSocketState::Listening
+    ) {
         return Err(KernelError::InvalidState {
             expected: "bound",
             actual: "not bound",
         });
     }
 
-    socket.backlog = backlog.min(UNIX_BACKLOG_MAX);
+    socket.backlog = backlog.clamp(1, UNIX_BACKLOG_MAX);
     socket.state = UnixSocketState::Listening;
     Ok(())
 }
 
-/// Connect a stream socket to a listening socket at the given path.
+/// Connect a socket to the socket bound at `path`.
 ///
-/// Returns Ok(()) on success. The connection is immediately established
-/// (no three-way handshake for local sockets).
+/// A stream socket must target a listening socket. The connection is
+/// established at once (no handshake for local sockets): the server side
+/// is created here and queued for `socket_accept`, so data sent before
+/// the accept is buffered. A datagram socket just records `path` as its
+/// default destination.
 pub fn socket_connect(socket_id: u64, path: &str) -> KernelResult<()> {
-    // Find the target socket by path.
-    let target_id = {
-        let paths = PATH_REGISTRY.lock();
-        *paths.get(path).ok_or(KernelError::NotFound {
-            resource: "unix_socket_path",
-            id: 0,
-        })?
-    };
-
+    let target_id = lookup_path(path)?;
     let mut sockets = UNIX_SOCKETS.lock();
 
-    // Verify target is listening.
-    let target = sockets.get(&target_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: target_id,
-    })?;
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    let (socket_type, cred) = (socket.socket_type, socket.owner);
+    if socket_type == UnixSocketType::Stream
+        && matches!(
+            socket.state,
+            UnixSocketState::Listening | UnixSocketState::Connected
+        )
+    {
+        return Err(KernelError::InvalidState {
+            expected: "unconnected",
+            actual: "listening or connected",
+        });
+    }
+
+    let target = sockets.get(&target_id).ok_or(not_found(target_id))?;
+    if target.socket_type != socket_type {
+        return Err(KernelError::InvalidArgument {
+            name: "path",
+            value: "socket type mismatch",
+        });
+    }
+
+    if socket_type == UnixSocketType::Datagram {
+        let target_cred = target.owner;
+        let socket = sockets.get_mut(&socket_id).ok_or(not_found(socket_id))?;
+        socket.peer_id = Some(target_id);
+        socket.peer_cred = Some(target_cred);
+        socket.state = UnixSocketState::Connected;
+        return Ok(());
+    }
 
     if target.state != UnixSocketState::Listening {
         return Err(KernelError::InvalidState {
@@ -269,26 +392,31 @@ pub fn socket_connect(socket_id: u64, path: &str) -> KernelResult<()> {
             actual: "not listening",
         });
     }
-
     if target.pending_connections.len() >= target.backlog {
+        return Err(KernelError::WouldBlock);
+    }
+    if sockets.len() >= UNIX_SOCKET_MAX {
         return Err(KernelError::ResourceExhausted {
-            resource: "connection backlog",
+            resource: "unix_sockets",
         });
     }
 
-    // Enqueue the connection request.
-    let target = sockets.get_mut(&target_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket_target",
-        id: target_id,
-    })?;
-    target.pending_connections.push_back(socket_id);
-
-    // Mark the connecting socket as connected (peer will be set on accept).
-    let socket = sockets.get_mut(&socket_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: socket_id,
-    })?;
-    socket.peer_id = Some(target_id);
+    // Until it is accepted, the server side goes away with the listener.
+    let target_cred = target.owner;
+    let server_id = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
+    let mut server = UnixSocket::new(server_id, UnixSocketType::Stream, target_cred);
+    server.state = UnixSocketState::Connected;
+    server.peer_id = Some(socket_id);
+    server.peer_cred = Some(cred);
+    server.path = sockets.get(&target_id).and_then(|t| t.path.clone());
+    sockets.insert(server_id, server);
+
+    if let Some(target) = sockets.get_mut(&target_id) {
+        target.pending_connections.push_back(server_id);
+    }
+    let socket = sockets.get_mut(&socket_id).ok_or(not_found(socket_id))?;
+    socket.peer_id = Some(server_id);
+    socket.peer_cred = Some(target_cred);
     socket.state = UnixSocketState::Connected;
 
     Ok(())
@@ -296,17 +424,14 @@ pub fn socket_connect(socket_id: u64, path: &str) -> KernelResult<()> {
 
 /// Accept a pending connection on a listening socket.
 ///
-/// Creates a new connected socket and returns its ID along with the
-/// connecting socket's ID.
+/// Returns the ID of the new connected socket along with the connecting
+/// socket's ID.
 pub fn socket_accept(listen_socket_id: u64) -> KernelResult<(u64, u64)> {
     let mut sockets = UNIX_SOCKETS.lock();
 
     let listen = sockets
         .get_mut(&listen_socket_id)
-        .ok_or(KernelError::NotFound {
-            resource: "unix_socket",
-            id: listen_socket_id,
-        })?;
+        .ok_or(not_found(listen_socket_id))?;
 
     if listen.state != UnixSocketState::Listening {
         return Err(KernelError::InvalidState {
@@ -315,41 +440,30 @@ pub fn socket_accept(listen_socket_id: u64) -> KernelResult<(u64, u64)> {
         });
     }
 
-    let connecting_id = listen
+    let new_id = listen
         .pending_connections
         .pop_front()
         .ok_or(KernelError::WouldBlock)?;
 
-    let owner_pid = listen.owner_pid;
-
-    // Create a new server-side socket for this connection.
-    let new_id = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
-    let mut new_socket = UnixSocket::new(new_id, UnixSocketType::Stream, owner_pid);
-    new_socket.state = UnixSocketState::Connected;
-    new_socket.peer_id = Some(connecting_id);
-
-    // Update the connecting socket's peer to point to the new server socket.
-    if let Some(connecting) = sockets.get_mut(&connecting_id) {
-        connecting.peer_id = Some(new_id);
-    }
-
-    sockets.insert(new_id, new_socket);
+    let new_socket = sockets.get(&new_id).ok_or(not_found(new_id))?;
+    let connecting_id = new_socket.peer_id.unwrap_or(0);
 
     Ok((new_id, connecting_id))
 }
 
 /// Send data on a connected socket.
 pub fn socket_send(socket_id: u64, data: &[u8], rights: Option<ScmRights>) -> KernelResult<usize> {
-    let sockets = UNIX_SOCKETS.lock();
-    let socket = sockets.get(&socket_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: socket_id,
-    })?;
+    check_rights(&rights)?;
+    let mut sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
 
-    if socket.shutdown_write {
-        return Err(KernelError::InvalidState {
-            expected: "write enabled",
-            actual: "shutdown for writing",
+    if socket.shutdown_write || socket.peer_closed {
+        return Err(KernelError::BrokenPipe);
+    }
+    if socket.socket_type == UnixSocketType::Datagram && data.len() > UNIX_DGRAM_MAX {
+        return Err(KernelError::InvalidArgument {
+            name: "data",
+            value: "exceeds UNIX_DGRAM_MAX",
         });
     }
 
@@ -357,153 +471,461 @@ pub fn socket_send(socket_id: u64, data: &[u8], rights: Option<ScmRights>) -> Ke
         expected: "connected",
         actual: "not connected",
     })?;
-    drop(sockets);
+    let sender_path = socket.path.clone();
 
-    // Deliver to peer's receive buffer.
-    let mut sockets = UNIX_SOCKETS.lock();
-    let peer = sockets.get_mut(&peer_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: peer_id,
-    })?;
-
-    if peer.shutdown_read {
-        return Err(KernelError::InvalidState {
-            expected: "read enabled",
-            actual: "peer shutdown for reading",
-        });
-    }
-
-    if peer.recv_buffer_used + data.len() > peer.recv_buffer_max {
-        return Err(KernelError::ResourceExhausted {
-            resource: "recv_buffer",
-        });
-    }
-
-    let msg = UnixMessage {
+    // A connected datagram socket whose target has gone away gets
+    // ECONNREFUSED on Linux; report the missing peer.
+    let peer = sockets.get_mut(&peer_id).ok_or(not_found(peer_id))?;
+    peer.has_room(data.len())?;
+    Ok(peer.enqueue(UnixMessage {
         data: data.to_vec(),
         rights,
         sender: socket_id,
-    };
-    let len = data.len();
-    peer.recv_buffer_used += len;
-    peer.recv_buffer.push_back(msg);
-
-    Ok(len)
+        sender_path,
+    }))
 }
 
 /// Receive data from a connected or datagram socket.
 ///
 /// Returns the number of bytes received and optional SCM_RIGHTS data.
 pub fn socket_recv(socket_id: u64, buf: &mut [u8]) -> KernelResult<(usize, Option<ScmRights>)> {
-    let mut sockets = UNIX_SOCKETS.lock();
-    let socket = sockets.get_mut(&socket_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: socket_id,
-    })?;
+    socket_recv_from(socket_id, buf).map(|r| (r.len, r.rights))
+}
 
-    if socket.shutdown_read {
-        return Ok((0, None)); // EOF
+/// Receive data along with the sender's address.
+///
+/// Stream sockets behave as a byte stream: a short buffer leaves the rest
+/// of a message queued, and consecutive messages are coalesced, except
+/// that a read never runs into a message carrying descriptors (they stay
+/// attached to its first byte). Datagram sockets return one message per
+/// call and drop whatever does not fit.
+///
+/// An empty buffer yields `WouldBlock`, or 0 (EOF) once the stream peer
+/// is gone or the socket is shut down for reading.
+pub fn socket_recv_from(socket_id: u64, buf: &mut [u8]) -> KernelResult<Received> {
+    let mut sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get_mut(&socket_id).ok_or(not_found(socket_id))?;
+
+    if socket.recv_buffer.is_empty() {
+        if socket.shutdown_read || socket.peer_closed {
+            return Ok(Received {
+                len: 0,
+                rights: None,
+                from: None,
+            });
+        }
+        return Err(KernelError::WouldBlock);
     }
 
-    let msg = socket
-        .recv_buffer
-        .pop_front()
-        .ok_or(KernelError::WouldBlock)?;
+    if socket.socket_type == UnixSocketType::Datagram {
+        let msg = socket
+            .recv_buffer
+            .pop_front()
+            .ok_or(KernelError::WouldBlock)?;
+        socket.recv_buffer_used = socket.recv_buffer_used.saturating_sub(msg.data.len());
+        let len = buf.len().min(msg.data.len());
+        buf[..len].copy_from_slice(&msg.data[..len]);
+        return Ok(Received {
+            len,
+            rights: msg.rights,
+            from: msg.sender_path,
+        });
+    }
 
-    let copy_len = buf.len().min(msg.data.len());
-    buf[..copy_len].copy_from_slice(&msg.data[..copy_len]);
-    socket.recv_buffer_used = socket.recv_buffer_used.saturating_sub(msg.data.len());
+    let mut copied = 0;
+    let mut rights = None;
+    while let Some(msg) = socket.recv_buffer.front_mut() {
+        if msg.rights.is_some() && copied > 0 {
+            break;
+        }
+        if let Some(r) = msg.rights.take() {
+            rights = Some(r);
+        }
+        let n = (buf.len() - copied).min(msg.data.len());
+        buf[copied..copied + n].copy_from_slice(&msg.data[..n]);
+        msg.data.drain(..n);
+        copied += n;
+        if msg.data.is_empty() {
+            socket.recv_buffer.pop_front();
+        }
+        if copied == buf.len() {
+            break;
+        }
+    }
+    socket.recv_buffer_used = socket.recv_buffer_used.saturating_sub(copied);
 
-    Ok((copy_len, msg.rights))
+    Ok(Received {
+        len: copied,
+        rights,
+        from: None,
+    })
 }
 
 /// Create an anonymous connected socket pair (socketpair).
 ///
 /// Returns (socket_a_id, socket_b_id) where both sockets are connected.
-pub fn socketpair(socket_type: UnixSocketType, owner_pid: u64) -> KernelResult<(u64, u64)> {
+pub fn socketpair(socket_type: UnixSocketType, owner: Credentials) -> KernelResult<(u64, u64)> {
     let id_a = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
     let id_b = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
 
-    let mut sock_a = UnixSocket::new(id_a, socket_type, owner_pid);
-    let mut sock_b = UnixSocket::new(id_b, socket_type, owner_pid);
+    let mut sock_a = UnixSocket::new(id_a, socket_type, owner);
+    let mut sock_b = UnixSocket::new(id_b, socket_type, owner);
 
     sock_a.state = UnixSocketState::Connected;
     sock_a.peer_id = Some(id_b);
+    sock_a.peer_cred = Some(owner);
     sock_b.state = UnixSocketState::Connected;
     sock_b.peer_id = Some(id_a);
+    sock_b.peer_cred = Some(owner);
 
     let mut sockets = UNIX_SOCKETS.lock();
+    if sockets.len() + 2 > UNIX_SOCKET_MAX {
+        return Err(KernelError::ResourceExhausted {
+            resource: "unix_sockets",
+        });
+    }
     sockets.insert(id_a, sock_a);
     sockets.insert(id_b, sock_b);
 
     Ok((id_a, id_b))
 }
 
-/// Close a Unix socket.
+/// Close a Unix socket (its last descriptor went away).
 pub fn socket_close(socket_id: u64) -> KernelResult<()> {
-    let mut sockets = UNIX_SOCKETS.lock();
+    let mut removed = Vec::new();
+    {
+        let mut sockets = UNIX_SOCKETS.lock();
+        if !sockets.contains_key(&socket_id) {
+            return Err(not_found(socket_id));
+        }
+        destroy(&mut sockets, socket_id, &mut removed);
+    }
+    // Buffered messages may hold the last reference to other sockets,
+    // whose close needs the registry again.
+    drop(removed);
+    Ok(())
+}
+
+/// Tear down a socket: release its path, give a stream peer EOF and drop
+/// connections nobody accepted. The removed sockets, with any in-flight
+/// descriptors, are moved to `removed` for the caller to drop once the
+/// registry is unlocked.
+fn destroy(sockets: &mut BTreeMap<u64, UnixSocket>, socket_id: u64, removed: &mut Vec<UnixSocket>) {
+    let Some(mut socket) = sockets.remove(&socket_id) else {
+        return;
+    };
 
-    if let Some(socket) = sockets.remove(&socket_id) {
-        // Remove path binding if any.
-        if let Some(ref path) = socket.path {
-            PATH_REGISTRY.lock().remove(path);
+    if let Some(ref path) = socket.path {
+        let mut paths = PATH_REGISTRY.lock();
+        if paths.get(path) == Some(&socket_id) {
+            paths.remove(path);
         }
+    }
 
-        // Notify peer of disconnection.
-        if let Some(peer_id) = socket.peer_id {
-            if let Some(peer) = sockets.get_mut(&peer_id) {
-                peer.peer_id = None;
-                peer.shutdown_read = true;
-                peer.shutdown_write = true;
+    if socket.socket_type == UnixSocketType::Stream {
+        if let Some(peer) = socket.peer_id.and_then(|id| sockets.get_mut(&id)) {
+            if peer.peer_id == Some(socket_id) {
+                peer.peer_closed = true;
             }
         }
     }
 
-    Ok(())
+    for pending in core::mem::take(&mut socket.pending_connections) {
+        destroy(sockets, pending, removed);
+    }
+    removed.push(socket);
 }
 
 /// Send a datagram to a named socket (connectionless).
-pub fn socket_sendto(socket_id: u64, data: &[u8], dest_path: &str) -> KernelResult<usize> {
+pub fn socket_sendto(
+    socket_id: u64,
+    data: &[u8],
+    dest_path: &str,
+    rights: Option<ScmRights>,
+) -> KernelResult<usize> {
     if data.len() > UNIX_DGRAM_MAX {
         return Err(KernelError::InvalidArgument {
             name: "data",
             value: "exceeds UNIX_DGRAM_MAX",
         });
     }
+    check_rights(&rights)?;
 
-    let dest_id = {
-        let paths = PATH_REGISTRY.lock();
-        *paths.get(dest_path).ok_or(KernelError::NotFound {
-            resource: "unix_socket_path",
-            id: 0,
-        })?
-    };
-
+    let dest_id = lookup_path(dest_path)?;
     let mut sockets = UNIX_SOCKETS.lock();
-    let dest = sockets.get_mut(&dest_id).ok_or(KernelError::NotFound {
-        resource: "unix_socket",
-        id: dest_id,
-    })?;
 
-    if dest.recv_buffer_used + data.len() > dest.recv_buffer_max {
-        return Err(KernelError::ResourceExhausted {
-            resource: "recv_buffer",
-        });
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    if socket.socket_type != UnixSocketType::Datagram {
+        // Stream sockets ignore the address, as on Linux.
+        drop(sockets);
+        return socket_send(socket_id, data, rights);
+    }
+    if socket.shutdown_write {
+        return Err(KernelError::BrokenPipe);
     }
+    let sender_path = socket.path.clone();
 
-    let msg = UnixMessage {
+    let dest = sockets.get_mut(&dest_id).ok_or(not_found(dest_id))?;
+    if dest.socket_type != UnixSocketType::Datagram {
+        return Err(KernelError::InvalidArgument {
+            name: "dest_path",
+            value: "socket type mismatch",
+        });
+    }
+    dest.has_room(data.len())?;
+    Ok(dest.enqueue(UnixMessage {
         data: data.to_vec(),
-        rights: None,
+        rights,
         sender: socket_id,
+        sender_path,
+    }))
+}
+
+/// The path a socket is bound to (getsockname).
+pub fn local_path(socket_id: u64) -> KernelResult<Option<String>> {
+    let sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    Ok(socket.path.clone())
+}
+
+/// The path of the connected peer (getpeername).
+pub fn peer_path(socket_id: u64) -> KernelResult<Option<String>> {
+    let sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    let peer_id = socket.peer_id.ok_or(KernelError::InvalidState {
+        expected: "connected",
+        actual: "not connected",
+    })?;
+    Ok(sockets.get(&peer_id).and_then(|p| p.path.clone()))
+}
+
+/// The peer's credentials (SO_PEERCRED).
+pub fn peer_credentials(socket_id: u64) -> KernelResult<Credentials> {
+    let sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    socket.peer_cred.ok_or(KernelError::InvalidState {
+        expected: "connected",
+        actual: "not connected",
+    })
+}
+
+/// The type of a socket (SO_TYPE).
+pub fn socket_type(socket_id: u64) -> KernelResult<UnixSocketType> {
+    let sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    Ok(socket.socket_type)
+}
+
+/// Set a SOL_SOCKET option. SO_RCVBUF also resizes the receive buffer.
+pub fn set_option(socket_id: u64, option: SocketOption) -> KernelResult<()> {
+    let mut sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get_mut(&socket_id).ok_or(not_found(socket_id))?;
+    match option {
+        SocketOption::RecvBufferSize(size) => {
+            socket.options.recv_buffer_size = size;
+            socket.recv_buffer_max = size;
+        }
+        SocketOption::SendBufferSize(size) => socket.options.send_buffer_size = size,
+        SocketOption::RecvTimeout(t) => socket.options.recv_timeout_ms = t,
+        SocketOption::SendTimeout(t) => socket.options.send_timeout_ms = t,
+        SocketOption::ReuseAddr(v) => socket.options.reuse_addr = v,
+        SocketOption::ReusePort(v) => socket.options.reuse_port = v,
+        SocketOption::Broadcast(v) => socket.options.broadcast = v,
+        SocketOption::KeepAlive(v) => socket.options.keepalive = v,
+        // TCP-level; meaningless for local sockets
+        SocketOption::NoDelay(_) => {}
+    }
+    Ok(())
+}
+
+/// The options of a socket (getsockopt).
+pub fn options(socket_id: u64) -> KernelResult<SocketOptions> {
+    let sockets = UNIX_SOCKETS.lock();
+    let socket = sockets.get(&socket_id).ok_or(not_found(socket_id))?;
+    Ok(socket.options)
+}
+
+/// Readiness for poll/epoll, as POLL* bits.
+///
+/// A listener is readable with a connection to accept. A connected socket
+/// is readable with data queued or once the stream peer is gone (the read
+/// returns EOF, and POLLHUP is raised as well), and writable while the
+/// peer's buffer has room. A socket that no longer exists reports POLLNVAL.
+pub fn poll_readiness(socket_id: u64) -> u16 {
+    const POLLIN: u16 = 0x0001;
+    const POLLOUT: u16 = 0x0004;
+    const POLLHUP: u16 = 0x0010;
+    const POLLNVAL: u16 = 0x0020;
+
+    let sockets = UNIX_SOCKETS.lock();
+    let Some(socket) = sockets.get(&socket_id) else {
+        return POLLNVAL;
     };
-    let len = data.len();
-    dest.recv_buffer_used += len;
-    dest.recv_buffer.push_back(msg);
 
-    Ok(len)
+    if socket.state == UnixSocketState::Listening {
+        return if socket.pending_connections.is_empty() {
+            0
+        } else {
+            POLLIN
+        };
+    }
+
+    let mut ready = 0;
+    if !socket.recv_buffer.is_empty() || socket.shutdown_read {
+        ready |= POLLIN;
+    }
+    if socket.peer_closed {
+        ready |= POLLIN | POLLHUP;
+    }
+    let peer_has_room = match socket.peer_id {
+        Some(peer) => sockets
+            .get(&peer)
+            .is_some_and(|p| p.recv_buffer_used < p.recv_buffer_max),
+        // An unconnected datagram socket can always sendto()
+        None => socket.socket_type == UnixSocketType::Datagram,
+    };
+    if peer_has_room && !socket.peer_closed && !socket.shutdown_write {
+        ready |= POLLOUT;
+    }
+    ready
 }
 
 /// Get the number of active Unix sockets.
 pub fn socket_count() -> usize {
     UNIX_SOCKETS.lock().len()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cred(pid: u64) -> Credentials {
+        Credentials {
+            pid,
+            uid: 1000,
+            gid: 1000,
+        }
+    }
+
+    fn listener(path: &str, pid: u64) -> u64 {
+        let id = socket_create(UnixSocketType::Stream, cred(pid)).unwrap();
+        socket_bind(id, path).unwrap();
+        socket_listen(id, 4).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_stream_is_a_byte_stream() {
+        let (a, b) = socketpair(UnixSocketType::Stream, cred(1)).unwrap();
+        socket_send(a, b"hello ", None).unwrap();
+        socket_send(a, b"world", None).unwrap();
+
+        let mut buf = [0u8; 4];
+        assert_eq!(socket_recv(b, &mut buf).unwrap().0, 4);
+        assert_eq!(&buf, b"hell");
+        let mut buf = [0u8; 16];
+        let n = socket_recv(b, &mut buf).unwrap().0;
+        assert_eq!(&buf[..n], b"o world");
+        assert!(matches!(
+            socket_recv(b, &mut buf),
+            Err(KernelError::WouldBlock)
+        ));
+
+        socket_close(a).unwrap();
+        assert_eq!(socket_recv(b, &mut buf).unwrap().0, 0);
+        assert!(matches!(
+            socket_send(b, b"x", None),
+            Err(KernelError::BrokenPipe)
+        ));
+        socket_close(b).unwrap();
+    }
+
+    #[test]
+    fn test_connect_before_accept() {
+        let server = listener("/run/test-accept.sock", 10);
+        let client = socket_create(UnixSocketType::Stream, cred(11)).unwrap();
+        socket_connect(client, "/run/test-accept.sock").unwrap();
+        socket_send(client, b"early", None).unwrap();
+
+        let (conn, peer) = socket_accept(server).unwrap();
+        assert_eq!(peer, client);
+        assert_eq!(peer_credentials(conn).unwrap().pid, 11);
+        assert_eq!(peer_credentials(client).unwrap().pid, 10);
+
+        let mut buf = [0u8; 8];
+        let n = socket_recv(conn, &mut buf).unwrap().0;
+        assert_eq!(&buf[..n], b"early");
+
+        for id in [client, conn, server] {
+            socket_close(id).unwrap();
+        }
+        assert!(lookup_path("/run/test-accept.sock").is_err());
+    }
+
+    #[test]
+    fn test_listener_close_drops_pending() {
+        let server = listener("/run/test-pending.sock", 20);
+        let client = socket_create(UnixSocketType::Stream, cred(21)).unwrap();
+        socket_connect(client, "/run/test-pending.sock").unwrap();
+
+        socket_close(server).unwrap();
+        assert!(local_path(server).is_err());
+        let mut buf = [0u8; 4];
+        assert_eq!(socket_recv(client, &mut buf).unwrap().0, 0);
+        socket_close(client).unwrap();
+    }
+
+    #[test]
+    fn test_datagram_addressing() {
+        let rx = socket_create(UnixSocketType::Datagram, cred(30)).unwrap();
+        socket_bind(rx, "/run/test-dgram.sock").unwrap();
+        let tx = socket_create(UnixSocketType::Datagram, cred(30)).unwrap();
+        socket_bind(tx, "/run/test-dgram-tx.sock").unwrap();
+
+        socket_sendto(tx, b"one", "/run/test-dgram.sock", None).unwrap();
+        socket_connect(tx, "/run/test-dgram.sock").unwrap();
+        socket_send(tx, b"two-long", None).unwrap();
+
+        let mut buf = [0u8; 4];
+        let r = socket_recv_from(rx, &mut buf).unwrap();
+        assert_eq!(r.len, 3);
+        assert_eq!(r.from.as_deref(), Some("/run/test-dgram-tx.sock"));
+        // Truncated, and the rest of the datagram is gone
+        assert_eq!(socket_recv(rx, &mut buf).unwrap().0, 4);
+        assert!(matches!(
+            socket_recv(rx, &mut buf),
+            Err(KernelError::WouldBlock)
+        ));
+
+        socket_close(tx).unwrap();
+        socket_close(rx).unwrap();
+    }
+
+    #[test]
+    fn test_poll_readiness() {
+        const POLLIN: u16 = 0x0001;
+        const POLLOUT: u16 = 0x0004;
+        const POLLHUP: u16 = 0x0010;
+
+        let server = listener("/run/test-poll.sock", 40);
+        assert_eq!(poll_readiness(server), 0);
+        let client = socket_create(UnixSocketType::Stream, cred(41)).unwrap();
+        socket_connect(client, "/run/test-poll.sock").unwrap();
+        assert_eq!(poll_readiness(server), POLLIN);
+
+        let (conn, _) = socket_accept(server).unwrap();
+        assert_eq!(poll_readiness(conn), POLLOUT);
+        socket_send(client, b"ping", None).unwrap();
+        assert_eq!(poll_readiness(conn), POLLIN | POLLOUT);
+
+        // A full peer buffer stops POLLOUT
+        set_option(conn, SocketOption::RecvBufferSize(4)).unwrap();
+        assert_eq!(poll_readiness(client), 0);
+
+        socket_close(client).unwrap();
+        assert_eq!(poll_readiness(conn), POLLIN | POLLHUP);
+        socket_close(conn).unwrap();
+        socket_close(server).unwrap();
+        assert_eq!(poll_readiness(conn), 0x0020);
+    }
+}
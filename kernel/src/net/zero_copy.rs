@@ -13,13 +13,14 @@
 //! 6. **Memory Mapping**: mmap() network buffers to user space
 //! 7. **TcpZeroCopySend**: Combined scatter-gather + TCP segmentation
 
-use alloc::{collections::VecDeque, vec, vec::Vec};
+use alloc::{collections::VecDeque, sync::Arc, vec, vec::Vec};
 use core::sync::atomic::{AtomicU64, Ordering};
 
 use spin::Mutex;
 
 use crate::{
     error::KernelError,
+    fs::file::File,
     mm::{phys_to_virt_addr, FRAME_ALLOCATOR, FRAME_SIZE},
 };
 
@@ -413,73 +414,152 @@ impl Default for ZeroCopySend {
     }
 }
 
-/// Kernel-to-kernel transfer (sendfile equivalent)
+/// One end of a [`SendFile`] transfer.
+pub enum SpliceEnd {
+    /// A file, pipe or device from the process file table.
+    File(Arc<File>),
+    /// A connected INET socket, by socket table ID.
+    InetSocket(usize),
+}
+
+impl SpliceEnd {
+    /// Read into `buf`, at `offset` if given, else at the file position.
+    fn read(&self, offset: Option<u64>, buf: &mut [u8]) -> Result<usize, KernelError> {
+        match self {
+            Self::File(file) => match offset {
+                Some(off) => {
+                    if !file.flags.read {
+                        return Err(KernelError::PermissionDenied {
+                            operation: "read file not opened for reading",
+                        });
+                    }
+                    file.node.read(off as usize, buf)
+                }
+                None => file.read(buf),
+            },
+            Self::InetSocket(id) => crate::net::socket::with_socket_mut(*id, |s| s.recv(buf, 0))?,
+        }
+    }
+
+    /// Write `data`, at `offset` if given, else at the file position.
+    fn write(&self, offset: Option<u64>, data: &[u8]) -> Result<usize, KernelError> {
+        match self {
+            Self::File(file) => match offset {
+                Some(off) => {
+                    if !file.flags.write {
+                        return Err(KernelError::PermissionDenied {
+                            operation: "write file not opened for writing",
+                        });
+                    }
+                    file.node.write(off as usize, data)
+                }
+                None => file.write(data),
+            },
+            Self::InetSocket(id) => crate::net::socket::with_socket_mut(*id, |s| s.send(data, 0))?,
+        }
+    }
+
+    /// Whether data written here can go out straight from page frames.
+    ///
+    /// Only sockets qualify, and only when the egress device gathers
+    /// frames itself; files and pipes always copy into their own storage.
+    fn supports_scatter_gather(&self) -> bool {
+        match self {
+            Self::File(_) => false,
+            Self::InetSocket(_) => {
+                let sg = |dev: &dyn crate::net::device::NetworkDevice| {
+                    dev.capabilities().supports_scatter_gather
+                };
+                crate::net::device::with_device("eth0", sg)
+                    .or_else(|| crate::net::device::with_device("lo0", sg))
+                    .unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// Page frames reused by scatter-gather transfers.
+static SENDFILE_POOL: spin::Once<DmaBufferPool> = spin::Once::new();
+
+/// Kernel-to-kernel transfer (the engine behind `sendfile` and `splice`).
+///
+/// Data moves from `source` to `dest` through kernel buffers only. An
+/// explicit offset reads or writes that end like `pread`/`pwrite`, leaving
+/// its file position alone; without one the file position is used and
+/// advanced.
 pub struct SendFile {
-    /// Source file descriptor
-    source_fd: u32,
-    /// Destination socket
-    dest_socket: u32,
-    /// Offset in source
-    offset: u64,
+    /// Where the data comes from
+    source: SpliceEnd,
+    /// Where the data goes
+    dest: SpliceEnd,
+    /// Offset in the source, if positional
+    source_offset: Option<u64>,
+    /// Offset in the destination, if positional
+    dest_offset: Option<u64>,
     /// Bytes to transfer
     count: usize,
 }
 
 impl SendFile {
+    /// Transfers at least this large try the scatter-gather path.
+    const SG_THRESHOLD: usize = 65536;
+    /// Frames filled per scatter-gather round.
+    const SG_BATCH: usize = 16;
+
     /// Create new sendfile operation
-    pub fn new(source_fd: u32, dest_socket: u32, offset: u64, count: usize) -> Self {
+    pub fn new(
+        source: SpliceEnd,
+        source_offset: Option<u64>,
+        dest: SpliceEnd,
+        dest_offset: Option<u64>,
+        count: usize,
+    ) -> Self {
         Self {
-            source_fd,
-            dest_socket,
-            offset,
+            source,
+            dest,
+            source_offset,
+            dest_offset,
             count,
         }
     }
 
     /// Execute transfer without copying to user space.
     ///
-    /// For large transfers (>= 64KB), uses scatter-gather to read file data
-    /// into DMA buffers and assemble once, reducing intermediate copies.
-    /// For smaller transfers, falls back to 4KB chunked copy through kernel
-    /// buffers.
+    /// Large transfers to a scatter-gather capable destination are read into
+    /// pooled page frames and written from there. Everything else, including
+    /// a scatter-gather transfer that cannot get frames, goes through a 4 KB
+    /// bounce buffer.
+    ///
+    /// Returns the bytes transferred. An error after some data has moved
+    /// (a full pipe, a socket that would block) ends the transfer early
+    /// instead of being reported, as for a short `write`.
     pub fn execute(&self) -> Result<usize, KernelError> {
-        let proc = crate::process::current_process().ok_or(KernelError::InvalidState {
-            expected: "running process",
-            actual: "no current process",
-        })?;
-        let ft = proc.file_table.lock();
-        let source_file = ft
-            .get(self.source_fd as usize)
-            .ok_or(KernelError::FsError(crate::error::FsError::NotFound))?;
-        let dest_file = ft
-            .get(self.dest_socket as usize)
-            .ok_or(KernelError::FsError(crate::error::FsError::NotFound))?;
-
-        // Seek source to the requested offset (ignore result for non-seekable)
-        let _ = self.offset;
-
-        // For large transfers, attempt scatter-gather path
-        if self.count >= 65536 {
-            if let Ok(transferred) = self.execute_sg(&source_file, &dest_file) {
+        if self.count >= Self::SG_THRESHOLD && self.dest.supports_scatter_gather() {
+            let pool = SENDFILE_POOL.call_once(|| DmaBufferPool::new(FRAME_SIZE, 0));
+            if let Some(first) = pool.alloc() {
+                let transferred = self.execute_sg(pool, first)?;
                 ZERO_COPY_STATS.record_zero_copy(transferred as u64);
                 return Ok(transferred);
             }
-            // SG path failed, fall through to copy path
         }
 
-        // Fallback: transfer in 4 KB chunks to avoid large stack allocations
-        let mut transferred = 0usize;
         let mut buf = [0u8; 4096];
+        let mut transferred = 0usize;
 
         while transferred < self.count {
             let chunk = core::cmp::min(buf.len(), self.count - transferred);
-            let n = source_file.read(&mut buf[..chunk])?;
-            if n == 0 {
-                break; // EOF
-            }
-            let written = dest_file.write(&buf[..n])?;
+            let n = match self
+                .source
+                .read(self.source_at(transferred), &mut buf[..chunk])
+            {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(_) if transferred > 0 => break,
+                Err(e) => return Err(e),
+            };
+            let written = self.write_all(transferred, &buf[..n])?;
             transferred += written;
-            if written == 0 {
+            if written < n {
                 break;
             }
         }
@@ -490,56 +570,93 @@ impl SendFile {
 
     /// Scatter-gather sendfile path.
     ///
-    /// Reads data from the source file into page-sized DMA buffers, adds them
-    /// to a scatter-gather list, then writes the assembled data to the
-    /// destination. This reduces copies compared to the 4KB loop by reading
-    /// into pre-allocated DMA buffers and assembling once.
-    fn execute_sg(
-        &self,
-        source: &crate::fs::file::File,
-        dest: &crate::fs::file::File,
-    ) -> Result<usize, KernelError> {
-        let mut sg = ScatterGatherList::new();
-        let mut dma_buffers: Vec<DmaBuffer> = Vec::new();
-        let mut total_read = 0usize;
-
-        // Read source data into DMA buffers, building the SG list
-        while total_read < self.count {
-            let mut dma_buf = DmaBuffer::new(FRAME_SIZE)?;
-            let to_read = core::cmp::min(FRAME_SIZE, self.count - total_read);
-            let n = source.read(&mut dma_buf.as_mut_slice()[..to_read])?;
-            if n == 0 {
-                break; // EOF
+    /// Fills up to [`Self::SG_BATCH`] pooled frames per round, records them
+    /// in a scatter-gather list and writes each segment straight from its
+    /// frame, with no intermediate assembly. Frames go back to the pool
+    /// after every round.
+    fn execute_sg(&self, pool: &DmaBufferPool, first: DmaBuffer) -> Result<usize, KernelError> {
+        let mut spare = Some(first);
+        let mut transferred = 0usize;
+
+        'rounds: while transferred < self.count {
+            let mut sg = ScatterGatherList::new();
+            let mut frames: Vec<DmaBuffer> = Vec::new();
+            let mut read = transferred;
+            let mut eof = false;
+
+            while frames.len() < Self::SG_BATCH && read < self.count {
+                let Some(mut frame) = spare.take().or_else(|| pool.alloc()) else {
+                    break;
+                };
+                let to_read = core::cmp::min(FRAME_SIZE, self.count - read);
+                let n = match self
+                    .source
+                    .read(self.source_at(read), &mut frame.as_mut_slice()[..to_read])
+                {
+                    Ok(n) => n,
+                    Err(_) if read > 0 => 0,
+                    Err(e) => {
+                        pool.free(frame);
+                        frames.into_iter().for_each(|f| pool.free(f));
+                        return Err(e);
+                    }
+                };
+                if n == 0 {
+                    pool.free(frame);
+                    eof = true;
+                    break;
+                }
+                sg.add_segment(frame.physical_addr, n);
+                read += n;
+                frames.push(frame);
             }
 
-            sg.add_segment(dma_buf.physical_addr, n);
-            total_read += n;
-            dma_buffers.push(dma_buf);
-        }
+            let mut short = false;
+            for (frame, seg) in frames.iter().zip(sg.segments()) {
+                let written = match self.write_all(transferred, &frame.as_slice()[..seg.length]) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        frames.into_iter().for_each(|f| pool.free(f));
+                        return Err(e);
+                    }
+                };
+                transferred += written;
+                if written < seg.length {
+                    short = true;
+                    break;
+                }
+            }
+            let empty = frames.is_empty();
+            frames.into_iter().for_each(|f| pool.free(f));
 
-        if total_read == 0 {
-            return Ok(0);
+            if eof || short || empty {
+                break 'rounds;
+            }
         }
 
-        // Assemble and write to destination
-        let assembled = sg.assemble()?;
-        let mut written_total = 0usize;
-        let mut write_offset = 0usize;
+        Ok(transferred)
+    }
 
-        while write_offset < assembled.len() {
-            let n = dest.write(&assembled[write_offset..])?;
-            if n == 0 {
-                break;
+    /// Source offset after `done` bytes, if the source is positional.
+    fn source_at(&self, done: usize) -> Option<u64> {
+        self.source_offset.map(|off| off + done as u64)
+    }
+
+    /// Write all of `data` to the destination, which has taken `done` bytes
+    /// so far. Returns fewer bytes than `data.len()` only when the
+    /// destination stopped accepting data after this transfer made progress.
+    fn write_all(&self, done: usize, data: &[u8]) -> Result<usize, KernelError> {
+        let mut written = 0usize;
+        while written < data.len() {
+            let at = self.dest_offset.map(|off| off + (done + written) as u64);
+            match self.dest.write(at, &data[written..]) {
+                Ok(0) => break,
+                Ok(n) => written += n,
+                Err(_) if done + written > 0 => break,
+                Err(e) => return Err(e),
             }
-            write_offset += n;
-            written_total += n;
         }
-
-        // DMA buffers are freed when dma_buffers Vec drops (frames leak in current
-        // implementation -- acceptable since DmaBuffer doesn't impl Drop for frame
-        // reclamation yet; this matches the pool-based pattern in dma_pool.rs)
-
-        Ok(written_total)
+        Ok(written)
     }
 }
 
@@ -829,4 +946,40 @@ mod tests {
         let send = ZeroCopySend::new();
         assert!(send.sg_list().is_empty());
     }
+
+    #[test]
+    fn test_sendfile_positional_file_to_pipe() {
+        use crate::fs::{
+            file::OpenFlags,
+            pipe::{create_pipe, PipeWriteNode},
+            ramfs::RamFs,
+            Filesystem, Permissions,
+        };
+
+        let fs = RamFs::new();
+        let node = fs.root().create("src", Permissions::default()).unwrap();
+        node.write(0, b"hello world").unwrap();
+        let src = Arc::new(File::new(node, OpenFlags::read_only()));
+
+        let (reader, writer) = create_pipe().unwrap();
+        let pipe = File::new(
+            Arc::new(PipeWriteNode::new(writer)),
+            OpenFlags::write_only(),
+        );
+
+        let transfer = SendFile::new(
+            SpliceEnd::File(src.clone()),
+            Some(6),
+            SpliceEnd::File(Arc::new(pipe)),
+            None,
+            100,
+        );
+        assert_eq!(transfer.execute().unwrap(), 5);
+
+        let mut buf = [0u8; 16];
+        let n = reader.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"world");
+        // A positional read leaves the file position alone
+        assert_eq!(src.tell(), 0);
+    }
 }
@@ -5,7 +5,17 @@
 
 #![allow(dead_code)]
 
-use crate::bench::{black_box, cycles_to_ns, read_timestamp};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crate::{
+    bench::{black_box, cycles_to_ns, read_timestamp},
+    sched::{
+        smp,
+        workqueue::{self, Work},
+    },
+    sync::rcu::Rcu,
+};
 
 /// Phase 5 performance targets (nanoseconds, x86_64 with KVM)
 pub(crate) const TARGET_SYSCALL_NS: u64 = 500;
@@ -13,6 +23,11 @@ pub(crate) const TARGET_CONTEXT_SWITCH_NS: u64 = 10_000;
 pub(crate) const TARGET_IPC_SMALL_NS: u64 = 1_000;
 pub(crate) const TARGET_FRAME_ALLOC_NS: u64 = 2000;
 pub(crate) const TARGET_CAP_LOOKUP_NS: u64 = 100;
+/// Lookup in a small read-mostly table (mount table, routing table)
+pub(crate) const TARGET_TABLE_READ_NS: u64 = 100;
+/// The same lookup while readers on every other CPU and a writer on this
+/// one share the table
+pub(crate) const TARGET_CONTENDED_READ_NS: u64 = 250;
 
 /// Individual benchmark result
 pub(crate) struct BenchResult {
@@ -141,6 +156,200 @@ fn bench_sched_current() -> BenchResult {
     })
 }
 
+/// Table shared by the RCU and RwLock benchmarks.
+type Table = BTreeMap<u32, u32>;
+
+fn new_table() -> Table {
+    (0u32..16).map(|i| (i, i)).collect()
+}
+
+/// Benchmark: RCU read of a small table (mount-table style lookup)
+///
+/// Readers only touch this CPU's cache line, so the cost stays flat as
+/// CPUs are added; compare with `rwlock_read`, whose shared reader count
+/// bounces between CPUs.
+fn bench_rcu_read() -> BenchResult {
+    let table = Rcu::new(new_table());
+    run_bench("rcu_read", 1000, TARGET_TABLE_READ_NS, || {
+        black_box(table.read().get(&black_box(7)).copied());
+    })
+}
+
+/// Benchmark: RwLock read of the same table, the baseline for `rcu_read`
+fn bench_rwlock_read() -> BenchResult {
+    let table = spin::RwLock::new(new_table());
+    run_bench("rwlock_read", 1000, TARGET_TABLE_READ_NS, || {
+        black_box(table.read().get(&black_box(7)).copied());
+    })
+}
+
+/// Table updates made by the writer in a contended benchmark.
+const CONTENDED_WRITES: u32 = 100;
+/// Writer pause between updates, in spin-loop iterations.
+const CONTENDED_WRITE_GAP: u32 = 2000;
+/// Lookups made by each reader at most, so that a reader never outlives
+/// a writer stuck behind it.
+const CONTENDED_READS: u64 = 20_000;
+/// Reader pause between lookups, in spin-loop iterations. `spin::RwLock`
+/// does not prefer writers; without a gap, back-to-back readers on several
+/// CPUs can keep the reader count above zero and starve the writer.
+const CONTENDED_READ_GAP: u32 = 16;
+/// How long the writer waits for the readers to start, in milliseconds.
+const CONTENDED_START_MS: u64 = 100;
+
+/// Reader work item data: which table to read.
+const READ_RCU: usize = 0;
+const READ_RWLOCK: usize = 1;
+
+static RCU_TABLE: spin::Once<Rcu<Table>> = spin::Once::new();
+static RWLOCK_TABLE: spin::Once<spin::RwLock<Table>> = spin::Once::new();
+
+/// One reader per CPU; the writer's CPU leaves its slot unused.
+static READERS: [Work; smp::MAX_CPUS] =
+    [const { Work::new(contended_reader, READ_RCU) }; smp::MAX_CPUS];
+
+/// Results gathered from the readers of the current contended run.
+static STOP: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicUsize = AtomicUsize::new(0);
+static READS: AtomicU64 = AtomicU64::new(0);
+static TOTAL_NS: AtomicU64 = AtomicU64::new(0);
+static MIN_NS: AtomicU64 = AtomicU64::new(u64::MAX);
+static MAX_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Reader work function: time lookups until the writer is done or
+/// [`CONTENDED_READS`] have been made, then fold this CPU's figures into
+/// the shared results.
+fn contended_reader(table: usize) {
+    STARTED.fetch_add(1, Ordering::AcqRel);
+
+    let mut reads = 0u64;
+    let mut total = 0u64;
+    let mut min = u64::MAX;
+    let mut max = 0u64;
+    while reads < CONTENDED_READS && !STOP.load(Ordering::Acquire) {
+        let start = read_timestamp();
+        let value = if table == READ_RCU {
+            RCU_TABLE
+                .call_once(|| Rcu::new(new_table()))
+                .read()
+                .get(&black_box(7))
+                .copied()
+        } else {
+            RWLOCK_TABLE
+                .call_once(|| spin::RwLock::new(new_table()))
+                .read()
+                .get(&black_box(7))
+                .copied()
+        };
+        let ns = cycles_to_ns(read_timestamp().saturating_sub(start));
+        black_box(value);
+        reads += 1;
+        total += ns;
+        min = min.min(ns);
+        max = max.max(ns);
+        for _ in 0..CONTENDED_READ_GAP {
+            core::hint::spin_loop();
+        }
+    }
+
+    READS.fetch_add(reads, Ordering::Relaxed);
+    TOTAL_NS.fetch_add(total, Ordering::Relaxed);
+    MIN_NS.fetch_min(min, Ordering::Relaxed);
+    MAX_NS.fetch_max(max, Ordering::Relaxed);
+}
+
+/// Run a reader on every other online CPU while this CPU calls `write`
+/// [`CONTENDED_WRITES`] times, and report the readers' lookup latency.
+///
+/// Returns `None` when no other CPU is online or no reader got to run.
+fn run_contended(
+    name: &'static str,
+    table: usize,
+    mut write: impl FnMut(u32),
+) -> Option<BenchResult> {
+    let this_cpu = smp::current_cpu_id();
+    let cpus: Vec<u8> = (0..smp::MAX_CPUS as u8)
+        .filter(|&cpu| cpu != this_cpu)
+        .filter(|&cpu| smp::per_cpu(cpu).is_some_and(|data| data.cpu_info.is_online()))
+        .collect();
+    if cpus.is_empty() {
+        return None;
+    }
+
+    STOP.store(false, Ordering::Release);
+    STARTED.store(0, Ordering::Release);
+    READS.store(0, Ordering::Relaxed);
+    TOTAL_NS.store(0, Ordering::Relaxed);
+    MIN_NS.store(u64::MAX, Ordering::Relaxed);
+    MAX_NS.store(0, Ordering::Relaxed);
+
+    for &cpu in &cpus {
+        let reader = &READERS[cpu as usize];
+        reader.set_data(table);
+        workqueue::queue_work_on(cpu, reader);
+    }
+
+    // Readers start on their CPU's next tick; do not wait forever for one
+    // whose CPU is busy. A late reader sees `STOP` and returns at once.
+    let deadline = crate::arch::timer::get_timestamp_ms() + CONTENDED_START_MS;
+    while STARTED.load(Ordering::Acquire) < cpus.len()
+        && crate::arch::timer::get_timestamp_ms() < deadline
+    {
+        core::hint::spin_loop();
+    }
+
+    for i in 0..CONTENDED_WRITES {
+        write(i);
+        for _ in 0..CONTENDED_WRITE_GAP {
+            core::hint::spin_loop();
+        }
+    }
+
+    STOP.store(true, Ordering::Release);
+    for &cpu in &cpus {
+        workqueue::flush_work(&READERS[cpu as usize]);
+    }
+
+    let reads = READS.load(Ordering::Relaxed);
+    if reads == 0 {
+        return None;
+    }
+    Some(BenchResult {
+        name,
+        iterations: reads,
+        min_ns: MIN_NS.load(Ordering::Relaxed),
+        avg_ns: TOTAL_NS.load(Ordering::Relaxed) / reads,
+        max_ns: MAX_NS.load(Ordering::Relaxed),
+        target_ns: TARGET_CONTENDED_READ_NS,
+    })
+}
+
+/// Benchmark: RCU reads on every other CPU while this CPU publishes new
+/// versions of the table
+///
+/// Readers never wait for the writer, so only the cache misses on a newly
+/// published version show up in their latency.
+fn bench_rcu_contended() -> Option<BenchResult> {
+    let table = RCU_TABLE.call_once(|| Rcu::new(new_table()));
+    run_contended("rcu_contended", READ_RCU, |i| {
+        table.update(|t| {
+            t.insert(16 + i % 16, i);
+        });
+    })
+}
+
+/// Benchmark: RwLock reads under the same load, the baseline for
+/// `rcu_contended`
+///
+/// Every reader bounces the lock word between CPUs and stalls while the
+/// writer holds it.
+fn bench_rwlock_contended() -> Option<BenchResult> {
+    let table = RWLOCK_TABLE.call_once(|| spin::RwLock::new(new_table()));
+    run_contended("rwlock_contended", READ_RWLOCK, |i| {
+        table.write().insert(16 + i % 16, i);
+    })
+}
+
 /// Run all benchmarks and print results.
 pub(crate) fn run_all_benchmarks() {
     crate::println!("=== VeridianOS Phase 5 Performance Benchmarks ===");
@@ -156,7 +365,7 @@ pub(crate) fn run_all_benchmarks() {
     );
     crate::println!("{}", "-".repeat(68));
 
-    let benchmarks = [
+    let mut benchmarks = alloc::vec![
         bench_syscall_latency(),
         bench_frame_alloc(),
         bench_frame_alloc_global(),
@@ -164,7 +373,12 @@ pub(crate) fn run_all_benchmarks() {
         bench_atomic_counter(),
         bench_ipc_stats(),
         bench_sched_current(),
+        bench_rcu_read(),
+        bench_rwlock_read(),
     ];
+    let contended = [bench_rcu_contended(), bench_rwlock_contended()];
+    let skipped = contended.iter().any(Option::is_none);
+    benchmarks.extend(contended.into_iter().flatten());
 
     let mut passed = 0;
     let total = benchmarks.len();
@@ -193,6 +407,9 @@ pub(crate) fn run_all_benchmarks() {
         passed,
         total
     );
+    if skipped {
+        crate::println!("Contended benchmarks skipped: no readers ran on other CPUs");
+    }
 
     // Also show IPC fast path stats
     let (fast_count, fast_avg) = crate::ipc::fast_path::get_fast_path_stats();
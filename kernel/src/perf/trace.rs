@@ -4,15 +4,27 @@
 //! at runtime. When disabled, the overhead is a single atomic load (branch on
 //! `TRACING_ENABLED`). When enabled, events are written to a fixed-size ring
 //! buffer per CPU, requiring no heap allocation.
+//!
+//! Recording can be narrowed with an event-type mask and a PID filter, and
+//! every recorded event is also counted per CPU and type, so `trace stats`
+//! stays meaningful after the rings have wrapped. User space controls all of
+//! this through the `trace_ctl` syscall (see `syscall::debug`).
 
+use alloc::vec::Vec;
 use core::{
     cell::UnsafeCell,
-    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
 };
 
 /// Global tracing enable flag -- zero overhead when false (single atomic load).
 pub(crate) static TRACING_ENABLED: AtomicBool = AtomicBool::new(false);
 
+/// Bit `1 << type` set for each event type that is recorded.
+static EVENT_MASK: AtomicU32 = AtomicU32::new(ALL_EVENTS);
+
+/// Only record events while this process is current (0 = every process).
+static PID_FILTER: AtomicU64 = AtomicU64::new(0);
+
 /// Trace event types
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,9 +49,20 @@ pub(crate) enum TraceEventType {
     PageFault = 8,
     /// IPC slow path fallback
     IpcSlowPath = 9,
+    /// Page fault handled (resolved or signalled)
+    PageFaultDone = 10,
 }
 
+/// Number of trace event types.
+pub(crate) const NUM_EVENT_TYPES: usize = 11;
+
+/// Event mask with every type enabled.
+pub(crate) const ALL_EVENTS: u32 = (1 << NUM_EVENT_TYPES) - 1;
+
 /// A single trace event (32 bytes, cache-line friendly).
+///
+/// This is also the record layout `trace_ctl(TRACE_READ)` copies to user
+/// space, with `timestamp` converted to nanoseconds.
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub(crate) struct TraceEvent {
@@ -50,7 +73,9 @@ pub(crate) struct TraceEvent {
     /// CPU that generated this event
     pub(crate) cpu: u8,
     /// Padding for alignment
-    _pad: [u8; 6],
+    _pad: [u8; 2],
+    /// Thread running on `cpu` when the event fired (0 = none yet)
+    pub(crate) tid: u32,
     /// Event-specific data (e.g., PID, syscall number, frame number)
     pub(crate) data: [u64; 2],
 }
@@ -61,12 +86,31 @@ impl TraceEvent {
             timestamp: 0,
             event_type: 0,
             cpu: 0,
-            _pad: [0; 6],
+            _pad: [0; 2],
+            tid: 0,
             data: [0; 2],
         }
     }
 }
 
+/// Recording state and counters, as returned by `trace_ctl(TRACE_STATS)`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TraceStats {
+    /// 1 if tracing is enabled
+    pub(crate) enabled: u32,
+    /// Current event-type mask
+    pub(crate) event_mask: u32,
+    /// Current PID filter (0 = all)
+    pub(crate) pid_filter: u64,
+    /// Events recorded since the last clear
+    pub(crate) recorded: u64,
+    /// Events overwritten before they could be read
+    pub(crate) overwritten: u64,
+    /// Events recorded per type, summed over CPUs
+    pub(crate) per_type: [u64; NUM_EVENT_TYPES],
+}
+
 /// Number of events per CPU ring buffer (4096 events = 128KB per CPU)
 const RING_SIZE: usize = 4096;
 
@@ -113,22 +157,44 @@ impl TraceRing {
     }
 }
 
-/// Maximum CPUs for trace ring allocation
-const MAX_TRACE_CPUS: usize = 16;
+/// Tracing state of one CPU.
+struct CpuTrace {
+    ring: UnsafeCell<TraceRing>,
+    /// Recorded events per type.
+    counts: [AtomicU64; NUM_EVENT_TYPES],
+    /// PID and TID last switched in, maintained from the `SchedSwitchIn`
+    /// tracepoint so events can be attributed without taking the scheduler
+    /// lock.
+    pid: AtomicU64,
+    tid: AtomicU64,
+}
 
-/// Per-CPU trace rings with interior mutability.
-///
-/// Each CPU writes only to its own ring via `current_cpu_id()`.
-/// Reading is done with tracing disabled or from the shell (single-threaded).
-struct PerCpuTraceRings([UnsafeCell<TraceRing>; MAX_TRACE_CPUS]);
+impl CpuTrace {
+    const fn new() -> Self {
+        Self {
+            ring: UnsafeCell::new(TraceRing::new()),
+            counts: [const { AtomicU64::new(0) }; NUM_EVENT_TYPES],
+            pid: AtomicU64::new(0),
+            tid: AtomicU64::new(0),
+        }
+    }
 
-// SAFETY: Per-CPU access pattern ensures no data races -- each CPU only
-// writes to its own ring (indexed by cpu_id). Reading is done with tracing
-// disabled, preventing concurrent writes.
-unsafe impl Sync for PerCpuTraceRings {}
+    /// Events recorded into the ring, including overwritten ones.
+    fn total_events(&self) -> usize {
+        // SAFETY: Only the write index is read, which is atomic.
+        unsafe { (*self.ring.get()).total_events() }
+    }
+}
+
+// SAFETY: Each CPU writes only to its own ring. Rings are read, and reset,
+// with tracing disabled, so no CPU is writing concurrently. The other
+// fields are atomics.
+unsafe impl Sync for CpuTrace {}
 
-static TRACE_RINGS: PerCpuTraceRings =
-    PerCpuTraceRings([const { UnsafeCell::new(TraceRing::new()) }; MAX_TRACE_CPUS]);
+crate::define_per_cpu! {
+    /// Per-CPU trace rings and counters.
+    static TRACE: CpuTrace = CpuTrace::new();
+}
 
 /// Record a trace event (inline, minimal overhead).
 ///
@@ -139,20 +205,41 @@ pub(crate) fn trace_event(event_type: TraceEventType, data0: u64, data1: u64) {
     if !TRACING_ENABLED.load(Ordering::Relaxed) {
         return;
     }
+    record_event(event_type, data0, data1);
+}
+
+/// Slow path of `trace_event`, kept out of line so the disabled check stays
+/// a load and a branch at every tracepoint.
+#[inline(never)]
+fn record_event(event_type: TraceEventType, data0: u64, data1: u64) {
+    let cpu = crate::sched::percpu::cpu_id();
+    let trace = TRACE.on_cpu(cpu);
+    if event_type == TraceEventType::SchedSwitchIn {
+        trace.pid.store(data0, Ordering::Relaxed);
+        trace.tid.store(data1, Ordering::Relaxed);
+    }
+
+    if EVENT_MASK.load(Ordering::Relaxed) & (1 << event_type as u32) == 0 {
+        return;
+    }
+    let pid_filter = PID_FILTER.load(Ordering::Relaxed);
+    if pid_filter != 0 && trace.pid.load(Ordering::Relaxed) != pid_filter {
+        return;
+    }
 
-    let cpu = crate::sched::smp::current_cpu_id() as usize;
     let event = TraceEvent {
         timestamp: crate::bench::read_timestamp(),
         event_type: event_type as u8,
         cpu: cpu as u8,
-        _pad: [0; 6],
+        _pad: [0; 2],
+        tid: trace.tid.load(Ordering::Relaxed) as u32,
         data: [data0, data1],
     };
+    trace.counts[event_type as usize].fetch_add(1, Ordering::Relaxed);
 
-    // SAFETY: Each CPU writes only to its own ring. The cpu index is
-    // bounded by MAX_TRACE_CPUS via the min() call.
+    // SAFETY: Each CPU writes only to its own ring.
     unsafe {
-        (*TRACE_RINGS.0[cpu.min(MAX_TRACE_CPUS - 1)].get()).record(event);
+        (*trace.ring.get()).record(event);
     }
 }
 
@@ -171,6 +258,91 @@ pub(crate) fn is_enabled() -> bool {
     TRACING_ENABLED.load(Ordering::Relaxed)
 }
 
+/// Record only the event types whose bits are set in `mask`.
+pub(crate) fn set_event_mask(mask: u32) {
+    EVENT_MASK.store(mask & ALL_EVENTS, Ordering::Relaxed);
+}
+
+/// Current event-type mask.
+pub(crate) fn event_mask() -> u32 {
+    EVENT_MASK.load(Ordering::Relaxed)
+}
+
+/// Record only events raised while `pid` is running (0 = every process).
+pub(crate) fn set_pid_filter(pid: u64) {
+    PID_FILTER.store(pid, Ordering::Relaxed);
+}
+
+/// Current PID filter.
+pub(crate) fn pid_filter() -> u64 {
+    PID_FILTER.load(Ordering::Relaxed)
+}
+
+/// Empty the rings and zero the counters. Tracing is paused meanwhile.
+pub(crate) fn clear() {
+    let was_enabled = is_enabled();
+    disable();
+    for trace in TRACE.iter() {
+        // SAFETY: Tracing is disabled, so no CPU is writing to its ring.
+        unsafe { (*trace.ring.get()).write_idx.store(0, Ordering::Relaxed) };
+        for count in &trace.counts {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+    if was_enabled {
+        enable();
+    }
+}
+
+/// Snapshot of the recording state and counters.
+pub(crate) fn stats() -> TraceStats {
+    let mut per_type = [0u64; NUM_EVENT_TYPES];
+    for trace in TRACE.iter() {
+        for (total, count) in per_type.iter_mut().zip(&trace.counts) {
+            *total += count.load(Ordering::Relaxed);
+        }
+    }
+    let overwritten = TRACE
+        .iter()
+        .map(|trace| trace.total_events().saturating_sub(RING_SIZE) as u64)
+        .sum();
+    TraceStats {
+        enabled: is_enabled() as u32,
+        event_mask: event_mask(),
+        pid_filter: pid_filter(),
+        recorded: total_events() as u64,
+        overwritten,
+        per_type,
+    }
+}
+
+/// Collect the buffered events of every CPU, oldest first, keeping at most
+/// the newest `max`. Tracing is paused while the rings are read.
+pub(crate) fn collect(max: usize) -> Vec<TraceEvent> {
+    let was_enabled = is_enabled();
+    disable();
+
+    let mut events = Vec::new();
+    for trace in TRACE.iter() {
+        // SAFETY: Tracing is disabled, so no concurrent writes.
+        let ring = unsafe { &*trace.ring.get() };
+        events.extend(
+            ring.read_recent(RING_SIZE)
+                .filter(|e| e.timestamp != 0)
+                .copied(),
+        );
+    }
+
+    if was_enabled {
+        enable();
+    }
+
+    events.sort_unstable_by_key(|e| e.timestamp);
+    let skip = events.len().saturating_sub(max);
+    events.drain(..skip);
+    events
+}
+
 /// Dump trace events from all CPUs to serial output.
 ///
 /// Prints the most recent `count` events per CPU.
@@ -180,19 +352,19 @@ pub(crate) fn dump_trace(count: usize) {
 
     crate::println!("=== Trace Dump (most recent {} per CPU) ===", count);
     crate::println!(
-        "{:>12} {:>4} {:>18} {:>16} {:>16}",
+        "{:>12} {:>4} {:>6} {:>18} {:>16} {:>16}",
         "TIMESTAMP",
         "CPU",
+        "TID",
         "EVENT",
         "DATA0",
         "DATA1"
     );
 
-    #[allow(clippy::needless_range_loop)]
-    for cpu in 0..MAX_TRACE_CPUS {
+    for trace in TRACE.iter() {
         // SAFETY: We disabled tracing, so no concurrent writes.
         // We only read from the ring buffer.
-        let ring = unsafe { &*TRACE_RINGS.0[cpu].get() };
+        let ring = unsafe { &*trace.ring.get() };
         let total = ring.total_events();
         if total == 0 {
             continue;
@@ -204,9 +376,10 @@ pub(crate) fn dump_trace(count: usize) {
             }
             let name = event_type_name(event.event_type);
             crate::println!(
-                "{:>12} {:>4} {:>18} {:#016x} {:#016x}",
+                "{:>12} {:>4} {:>6} {:>18} {:#016x} {:#016x}",
                 event.timestamp,
                 event.cpu,
+                event.tid,
                 name,
                 event.data[0],
                 event.data[1]
@@ -214,10 +387,7 @@ pub(crate) fn dump_trace(count: usize) {
         }
     }
 
-    let total: usize = (0..MAX_TRACE_CPUS)
-        .map(|cpu| unsafe { (*TRACE_RINGS.0[cpu].get()).total_events() })
-        .sum();
-    crate::println!("=== Total events recorded: {} ===", total);
+    crate::println!("=== Total events recorded: {} ===", total_events());
 
     if was_enabled {
         enable(); // Re-enable if it was on
@@ -226,12 +396,11 @@ pub(crate) fn dump_trace(count: usize) {
 
 /// Get total events across all CPUs
 pub(crate) fn total_events() -> usize {
-    (0..MAX_TRACE_CPUS)
-        .map(|cpu| unsafe { (*TRACE_RINGS.0[cpu].get()).total_events() })
-        .sum()
+    TRACE.iter().map(CpuTrace::total_events).sum()
 }
 
-fn event_type_name(t: u8) -> &'static str {
+/// Short name of an event type, as shown by `trace dump`.
+pub(crate) fn event_type_name(t: u8) -> &'static str {
     match t {
         0 => "syscall_entry",
         1 => "syscall_exit",
@@ -243,10 +412,16 @@ fn event_type_name(t: u8) -> &'static str {
         7 => "frame_free",
         8 => "page_fault",
         9 => "ipc_slow_path",
+        10 => "page_fault_done",
         _ => "unknown",
     }
 }
 
+/// Event type number for a name accepted by `event_type_name`.
+pub(crate) fn event_type_from_name(name: &str) -> Option<u8> {
+    (0..NUM_EVENT_TYPES as u8).find(|&t| event_type_name(t) == name)
+}
+
 /// Convenience macro for recording trace events with zero overhead when
 /// disabled.
 #[macro_export]
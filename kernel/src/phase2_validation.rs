@@ -136,6 +136,7 @@ pub fn quick_health_check() -> bool {
     }
 
     // Check Network Manager
+    #[cfg(feature = "net")]
     if crate::drivers::network::is_network_initialized() {
         let network_manager = crate::drivers::network::get_network_manager();
         let interfaces = network_manager.list_interfaces();
@@ -18,6 +18,8 @@ pub mod delta;
 pub mod ecosystem;
 pub mod format;
 pub mod manifest;
+#[cfg(feature = "alloc")]
+pub mod pkgd_client;
 pub mod plugin;
 pub mod ports;
 #[cfg(feature = "alloc")]
@@ -29,6 +31,8 @@ pub mod sdk;
 pub mod statistics;
 pub mod testing;
 pub mod toml_parser;
+#[cfg(feature = "alloc")]
+pub mod vpk;
 
 use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
 
@@ -163,6 +167,11 @@ impl PackageManager {
         self.signature_policy = policy;
     }
 
+    /// Get a reference to the trusted key ring.
+    pub fn trusted_keys(&self) -> &format::TrustedKeyRing {
+        &self.trusted_keys
+    }
+
     /// Get a mutable reference to the trusted key ring.
     pub fn trusted_keys_mut(&mut self) -> &mut format::TrustedKeyRing {
         &mut self.trusted_keys
@@ -0,0 +1,184 @@
+//! IPC client for the user-space package service (`pkgd`)
+//!
+//! `pkgd` owns the real install pipeline (fetch, `.vpk` verification, file
+//! manifests, rollback); the `pkg` shell builtin forwards requests to it
+//! whenever it is running.
+//!
+//! `pkgd` binds its request endpoint as [`PKGD_SERVICE`] in the root
+//! namespace. A request is a large message whose opcode selects the
+//! operation, whose header flags carry a sequence number and whose payload
+//! is the NUL-terminated argument list. `pkgd` answers with a small message
+//! to the kernel's reply endpoint (bound as [`PKGD_REPLY_SERVICE`]):
+//! `data[0]` echoes the sequence number, `data[1]` is the status (0 or a
+//! negated errno) and `data[2]` the number of packages affected. Progress
+//! and listings are printed by `pkgd` itself on the console.
+//!
+//! The wire constants are mirrored in `userland/programs/pkgd/pkgd.h`.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use spin::Mutex;
+
+use crate::{
+    error::KernelError,
+    ipc::{
+        message::{MemoryRegion, MessageHeader, SMALL_MESSAGE_MAX_SIZE},
+        EndpointId, LargeMessage, Message, ProcessId,
+    },
+};
+
+/// Service name `pkgd` binds its request endpoint to.
+pub const PKGD_SERVICE: &str = "pkgd";
+/// Service name of the kernel endpoint `pkgd` sends replies to.
+pub const PKGD_REPLY_SERVICE: &str = "pkgd.reply";
+
+/// Fetch a package into the local cache: `<name|path|url>`
+pub const PKGD_OP_FETCH: u32 = 1;
+/// Fetch, verify and install: `<name|path|url>...`
+pub const PKGD_OP_INSTALL: u32 = 2;
+/// Remove installed packages: `<name>...`
+pub const PKGD_OP_REMOVE: u32 = 3;
+/// Upgrade installed packages (all if no arguments): `[name...]`
+pub const PKGD_OP_UPGRADE: u32 = 4;
+/// List installed packages
+pub const PKGD_OP_LIST: u32 = 5;
+/// Check installed files against their manifests: `[name...]`
+pub const PKGD_OP_VERIFY: u32 = 6;
+
+/// Maximum request payload size.
+const REQUEST_MAX: usize = 1024;
+
+/// How long to wait for `pkgd` to answer (installs can fetch and unpack).
+const REPLY_TIMEOUT_MS: u64 = 120_000;
+
+/// Requests are issued on behalf of the kernel, i.e. in the root namespace.
+const KERNEL_PID: ProcessId = ProcessId(0);
+
+/// Outcome of a `pkgd` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PkgdReply {
+    /// 0 on success, otherwise a negated errno
+    pub status: i64,
+    /// Number of packages installed/removed/upgraded/listed/verified
+    pub count: u64,
+}
+
+/// Request payload. Static so the buffer outlives a timed-out request that
+/// `pkgd` has not dequeued yet; the lock also serialises requests.
+static REQUEST: Mutex<[u8; REQUEST_MAX]> = Mutex::new([0; REQUEST_MAX]);
+
+/// Kernel reply endpoint, created on first use.
+static REPLY_ENDPOINT: Mutex<Option<EndpointId>> = Mutex::new(None);
+
+static NEXT_SEQ: AtomicU32 = AtomicU32::new(1);
+
+/// Whether `pkgd` has registered its endpoint.
+pub fn is_available() -> bool {
+    crate::ipc::namespace::lookup_name(KERNEL_PID, PKGD_SERVICE).is_ok()
+}
+
+fn reply_endpoint() -> Result<EndpointId, KernelError> {
+    let mut slot = REPLY_ENDPOINT.lock();
+    if let Some(id) = *slot {
+        return Ok(id);
+    }
+    let (id, _cap) =
+        crate::ipc::create_endpoint(KERNEL_PID).map_err(|_| KernelError::ResourceExhausted {
+            resource: "IPC endpoints",
+        })?;
+    crate::ipc::namespace::bind_name(KERNEL_PID, PKGD_REPLY_SERVICE, id).map_err(|_| {
+        KernelError::AlreadyExists {
+            resource: "pkgd reply service",
+            id,
+        }
+    })?;
+    *slot = Some(id);
+    Ok(id)
+}
+
+/// Pack `args` as consecutive NUL-terminated strings. Returns the length.
+fn encode_args(args: &[&str], buf: &mut [u8]) -> Result<usize, KernelError> {
+    let mut len = 0;
+    for arg in args {
+        let bytes = arg.as_bytes();
+        if bytes.contains(&0) || len + bytes.len() + 1 > buf.len() {
+            return Err(KernelError::InvalidArgument {
+                name: "pkgd request",
+                value: "arguments too long or contain NUL",
+            });
+        }
+        buf[len..len + bytes.len()].copy_from_slice(bytes);
+        len += bytes.len();
+        buf[len] = 0;
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// Send request `op` with `args` to `pkgd` and wait for its reply.
+pub fn request(op: u32, args: &[&str]) -> Result<PkgdReply, KernelError> {
+    let service = crate::ipc::namespace::lookup_name(KERNEL_PID, PKGD_SERVICE).map_err(|_| {
+        KernelError::NotFound {
+            resource: "pkgd service",
+            id: 0,
+        }
+    })?;
+    let reply_ep = reply_endpoint()?;
+
+    let mut buf = REQUEST.lock();
+    let len = encode_args(args, &mut buf[..])?;
+    let seq = NEXT_SEQ.fetch_add(1, Ordering::Relaxed);
+
+    // Discard replies to earlier requests that timed out
+    while crate::ipc::registry::try_receive_from_endpoint(reply_ep).is_some() {}
+
+    let mut header = MessageHeader::new(0, op, len as u64);
+    header.flags = seq;
+    let message = Message::Large(LargeMessage {
+        header,
+        memory_region: MemoryRegion::new(buf.as_ptr() as u64, len as u64),
+        inline_data: [0; SMALL_MESSAGE_MAX_SIZE],
+    });
+    crate::ipc::registry::lookup_endpoint(service)
+        .and_then(|endpoint| endpoint.send_async(message))
+        .map_err(|_| KernelError::NotFound {
+            resource: "pkgd endpoint",
+            id: service,
+        })?;
+
+    let deadline = crate::arch::timer::get_timestamp_ms() + REPLY_TIMEOUT_MS;
+    loop {
+        match crate::ipc::registry::try_receive_from_endpoint(reply_ep) {
+            Some(Message::Small(reply)) if reply.data[0] == seq as u64 => {
+                return Ok(PkgdReply {
+                    status: reply.data[1] as i64,
+                    count: reply.data[2],
+                });
+            }
+            Some(_) => continue,
+            None => {}
+        }
+        if crate::arch::timer::get_timestamp_ms() >= deadline {
+            return Err(KernelError::Timeout {
+                operation: "pkgd request",
+                duration_ms: REPLY_TIMEOUT_MS,
+            });
+        }
+        crate::sched::yield_cpu();
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_args() {
+        let mut buf = [0xFFu8; 16];
+        assert_eq!(encode_args(&["ab", "c"], &mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"ab\0c\0");
+        assert_eq!(encode_args(&[], &mut buf), Ok(0));
+        assert!(encode_args(&["0123456789abcdef"], &mut buf).is_err());
+        assert!(encode_args(&["a\0b"], &mut buf).is_err());
+    }
+}
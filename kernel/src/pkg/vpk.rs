@@ -0,0 +1,469 @@
+//! `.vpk` Package Archives
+//!
+//! The on-disk format fetched and installed by the user-space `pkgd`
+//! service: a TOML manifest, a compressed file archive and an Ed25519
+//! signature over both. `pkgd` parses the archive itself but asks the kernel
+//! to check the signature (`SYS_PKG_VERIFY`), so the trusted key ring never
+//! leaves the kernel.
+//!
+//! ## Layout (all integers little-endian)
+//!
+//! ```text
+//! offset  size  field
+//!      0     4  magic "VPK1"
+//!      4     2  format version (1)
+//!      6     1  archive compression (`format::Compression`)
+//!      7     1  reserved (0)
+//!      8     4  manifest size
+//!     12     4  archive size (compressed)
+//!     16    32  signer Ed25519 public key
+//!     48     -  manifest (TOML, UTF-8)
+//!      -     -  archive (compressed file table)
+//!      -    64  Ed25519 signature over every preceding byte
+//! ```
+//!
+//! The decompressed archive uses the same file table as `.vpkg` content: a
+//! `u32` file count, then per file a `u16` path length, the path, a `u64`
+//! size, a `u32` mode and the file data. Paths are absolute install paths.
+//!
+//! ## Manifest
+//!
+//! ```toml
+//! [package]
+//! name = "hello"
+//! version = "1.0.0"
+//! description = "Prints a greeting"
+//! license = "MIT"
+//! depends = ["libgreet"]
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+
+use super::{
+    format::{Compression, TrustLevel, TrustedKey, TrustedKeyRing},
+    Version,
+};
+use crate::error::KernelError;
+
+/// Archive magic number
+pub const VPK_MAGIC: [u8; 4] = *b"VPK1";
+
+/// Archive format version
+pub const VPK_VERSION: u16 = 1;
+
+/// Size of the fixed header
+pub const VPK_HEADER_SIZE: usize = 48;
+
+/// Size of the trailing Ed25519 signature
+pub const VPK_SIGNATURE_SIZE: usize = 64;
+
+/// Extra trusted signing keys, one `<hex public key> [trust level]` per line
+pub const TRUSTED_KEYS_PATH: &str = "/etc/pkg/trusted.keys";
+
+fn invalid(value: &'static str) -> KernelError {
+    KernelError::InvalidArgument { name: "vpk", value }
+}
+
+/// Package manifest (`[package]` table of the embedded TOML).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpkManifest {
+    pub name: String,
+    pub version: Version,
+    pub description: String,
+    pub license: String,
+    pub depends: Vec<String>,
+}
+
+impl VpkManifest {
+    /// Parse a manifest from TOML text.
+    pub fn parse(text: &str) -> Result<Self, KernelError> {
+        let root = super::toml_parser::parse_toml(text)?;
+        let package = root
+            .get("package")
+            .and_then(|v| v.as_table())
+            .ok_or(invalid("manifest has no [package] table"))?;
+        let string = |key: &str| {
+            package
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .unwrap_or_default()
+        };
+
+        let name = string("name");
+        if !is_valid_name(&name) {
+            return Err(invalid("bad package name"));
+        }
+        let version = package
+            .get("version")
+            .and_then(|v| v.as_str())
+            .ok_or(invalid("manifest has no version"))?;
+        let depends = package
+            .get("depends")
+            .and_then(|v| v.as_array())
+            .map(|deps| {
+                deps.iter()
+                    .filter_map(|d| d.as_str())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            name,
+            version: super::parse_version(version),
+            description: string("description"),
+            license: string("license"),
+            depends,
+        })
+    }
+
+    /// Render the manifest as TOML.
+    pub fn to_toml(&self) -> String {
+        let depends: Vec<String> = self.depends.iter().map(|d| format!("\"{}\"", d)).collect();
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"{}.{}.{}\"\ndescription = \"{}\"\nlicense = \
+             \"{}\"\ndepends = [{}]\n",
+            self.name,
+            self.version.major,
+            self.version.minor,
+            self.version.patch,
+            self.description,
+            self.license,
+            depends.join(", ")
+        )
+    }
+}
+
+/// Package names are short identifiers usable as file names.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.')
+        && !name.starts_with('.')
+}
+
+/// Install paths must be absolute and must not escape via `..`.
+fn is_valid_path(path: &str) -> bool {
+    path.starts_with('/') && path.len() > 1 && !path.split('/').any(|c| c == "..")
+}
+
+/// A file carried in the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpkFile {
+    /// Absolute install path
+    pub path: String,
+    /// Permission bits
+    pub mode: u32,
+    pub data: Vec<u8>,
+}
+
+/// A parsed (but not yet verified) `.vpk` archive borrowing its input.
+#[derive(Debug)]
+pub struct VpkArchive<'a> {
+    compression: Compression,
+    signer: [u8; 32],
+    manifest: &'a [u8],
+    archive: &'a [u8],
+    signed: &'a [u8],
+    signature: [u8; VPK_SIGNATURE_SIZE],
+}
+
+impl<'a> VpkArchive<'a> {
+    /// Split `data` into its sections, checking only the framing.
+    pub fn parse(data: &'a [u8]) -> Result<Self, KernelError> {
+        if data.len() < VPK_HEADER_SIZE + VPK_SIGNATURE_SIZE || data[0..4] != VPK_MAGIC {
+            return Err(invalid("not a .vpk archive"));
+        }
+        if u16::from_le_bytes([data[4], data[5]]) != VPK_VERSION {
+            return Err(invalid("unsupported .vpk version"));
+        }
+        let compression = Compression::from_u8(data[6]).ok_or(invalid("unknown compression"))?;
+        let u32_at = |off: usize| {
+            u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]]) as usize
+        };
+        let manifest_size = u32_at(8);
+        let archive_size = u32_at(12);
+
+        let manifest_end = VPK_HEADER_SIZE
+            .checked_add(manifest_size)
+            .ok_or(invalid("bad section sizes"))?;
+        let archive_end = manifest_end
+            .checked_add(archive_size)
+            .ok_or(invalid("bad section sizes"))?;
+        if archive_end + VPK_SIGNATURE_SIZE != data.len() {
+            return Err(invalid("section sizes do not match file size"));
+        }
+
+        let mut signer = [0u8; 32];
+        signer.copy_from_slice(&data[16..48]);
+        let mut signature = [0u8; VPK_SIGNATURE_SIZE];
+        signature.copy_from_slice(&data[archive_end..]);
+
+        Ok(Self {
+            compression,
+            signer,
+            manifest: &data[VPK_HEADER_SIZE..manifest_end],
+            archive: &data[manifest_end..archive_end],
+            signed: &data[..archive_end],
+            signature,
+        })
+    }
+
+    /// Public key the archive claims to be signed with.
+    pub fn signer(&self) -> &[u8; 32] {
+        &self.signer
+    }
+
+    /// Check the signature against `keys`, returning the signer's trust
+    /// level.
+    pub fn verify(&self, keys: &TrustedKeyRing) -> Result<TrustLevel, KernelError> {
+        use crate::crypto::asymmetric::{Signature, VerifyingKey};
+
+        let denied = KernelError::PermissionDenied {
+            operation: "verify package signature",
+        };
+        let key = keys.find_key(&self.signer).ok_or(denied)?;
+        let vk = VerifyingKey::from_bytes(&key.public_key).map_err(|_| denied)?;
+        let sig = Signature::from_bytes(&self.signature).map_err(|_| denied)?;
+        match vk.verify(self.signed, &sig) {
+            Ok(true) => Ok(key.trust_level),
+            _ => Err(denied),
+        }
+    }
+
+    /// Parse the embedded manifest.
+    pub fn manifest(&self) -> Result<VpkManifest, KernelError> {
+        let text =
+            core::str::from_utf8(self.manifest).map_err(|_| invalid("manifest not UTF-8"))?;
+        VpkManifest::parse(text)
+    }
+
+    /// Decompress and decode the file table.
+    pub fn files(&self) -> Result<Vec<VpkFile>, KernelError> {
+        let table = super::format::decompress(self.archive, self.compression)
+            .map_err(|_| invalid("archive decompression failed"))?;
+        decode_file_table(&table)
+    }
+}
+
+fn decode_file_table(data: &[u8]) -> Result<Vec<VpkFile>, KernelError> {
+    let truncated = invalid("truncated file table");
+    let take = |pos: &mut usize, len: usize| -> Result<&[u8], KernelError> {
+        let bytes = data.get(*pos..*pos + len).ok_or(truncated)?;
+        *pos += len;
+        Ok(bytes)
+    };
+
+    let mut pos = 0;
+    let count = u32::from_le_bytes(take(&mut pos, 4)?.try_into().unwrap_or([0; 4])) as usize;
+    let mut files = Vec::with_capacity(count.min(1024));
+    for _ in 0..count {
+        let path_len = u16::from_le_bytes(take(&mut pos, 2)?.try_into().unwrap_or([0; 2]));
+        let path = core::str::from_utf8(take(&mut pos, path_len as usize)?)
+            .map_err(|_| invalid("file path not UTF-8"))?;
+        if !is_valid_path(path) {
+            return Err(invalid("file path must be absolute without '..'"));
+        }
+        let size = u64::from_le_bytes(take(&mut pos, 8)?.try_into().unwrap_or([0; 8])) as usize;
+        let mode = u32::from_le_bytes(take(&mut pos, 4)?.try_into().unwrap_or([0; 4]));
+        let data = take(&mut pos, size)?.to_vec();
+        files.push(VpkFile {
+            path: String::from(path),
+            mode,
+            data,
+        });
+    }
+    Ok(files)
+}
+
+fn encode_file_table(files: &[VpkFile]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    for file in files {
+        out.extend_from_slice(&(file.path.len() as u16).to_le_bytes());
+        out.extend_from_slice(file.path.as_bytes());
+        out.extend_from_slice(&(file.data.len() as u64).to_le_bytes());
+        out.extend_from_slice(&file.mode.to_le_bytes());
+        out.extend_from_slice(&file.data);
+    }
+    out
+}
+
+/// Build and sign a `.vpk` archive with the Ed25519 key derived from `seed`.
+pub fn build(
+    manifest: &VpkManifest,
+    files: &[VpkFile],
+    compression: Compression,
+    seed: &[u8; 32],
+) -> Result<Vec<u8>, KernelError> {
+    use crate::crypto::asymmetric::KeyPair;
+
+    if files.iter().any(|f| !is_valid_path(&f.path)) {
+        return Err(invalid("file path must be absolute without '..'"));
+    }
+    let toml = manifest.to_toml();
+    let archive = super::format::compress(&encode_file_table(files), compression)
+        .map_err(|_| invalid("archive compression failed"))?;
+    let keypair = KeyPair::from_seed(seed).map_err(|_| invalid("bad signing seed"))?;
+
+    let mut out = Vec::with_capacity(VPK_HEADER_SIZE + toml.len() + archive.len() + 64);
+    out.extend_from_slice(&VPK_MAGIC);
+    out.extend_from_slice(&VPK_VERSION.to_le_bytes());
+    out.push(compression as u8);
+    out.push(0);
+    out.extend_from_slice(&(toml.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(archive.len() as u32).to_le_bytes());
+    out.extend_from_slice(keypair.verifying_key.as_bytes());
+    out.extend_from_slice(toml.as_bytes());
+    out.extend_from_slice(&archive);
+
+    let signature = keypair
+        .signing_key
+        .sign(&out)
+        .map_err(|_| invalid("signing failed"))?;
+    out.extend_from_slice(signature.as_bytes());
+    Ok(out)
+}
+
+/// Add the keys listed in a trusted-keys file to `ring`.
+///
+/// Each non-empty, non-`#` line holds a 64-digit hex Ed25519 public key,
+/// optionally followed by `core`, `developer` or `community` (default
+/// `community`). Malformed lines are skipped. Returns the number of keys
+/// added.
+pub fn load_trusted_keys(ring: &mut TrustedKeyRing, text: &str) -> usize {
+    let mut added = 0;
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        let Some(hex) = words.next().filter(|w| !w.starts_with('#')) else {
+            continue;
+        };
+        let Some(public_key) = parse_hex_key(hex) else {
+            continue;
+        };
+        let trust_level = match words.next() {
+            Some("core") => TrustLevel::Core,
+            Some("developer") => TrustLevel::Developer,
+            Some("community") | None => TrustLevel::Community,
+            Some(_) => continue,
+        };
+        if ring.find_key(&public_key).is_some() {
+            continue;
+        }
+        ring.add_key(TrustedKey {
+            public_key,
+            fingerprint: *crate::crypto::hash::sha256(&public_key).as_bytes(),
+            trust_level,
+        });
+        added += 1;
+    }
+    added
+}
+
+fn parse_hex_key(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(key)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::crypto::asymmetric::KeyPair;
+
+    const SEED: [u8; 32] = [7; 32];
+
+    fn sample() -> (VpkManifest, Vec<VpkFile>) {
+        let manifest = VpkManifest {
+            name: String::from("hello"),
+            version: Version::new(1, 2, 3),
+            description: String::from("Prints a greeting"),
+            license: String::from("MIT"),
+            depends: vec![String::from("libgreet")],
+        };
+        let files = vec![VpkFile {
+            path: String::from("/usr/bin/hello"),
+            mode: 0o755,
+            data: b"#!/bin/sh\necho hello hello hello hello\n".to_vec(),
+        }];
+        (manifest, files)
+    }
+
+    fn ring_with(seed: &[u8; 32], level: TrustLevel) -> TrustedKeyRing {
+        let pk = *KeyPair::from_seed(seed).unwrap().verifying_key.as_bytes();
+        let mut ring = TrustedKeyRing::new();
+        ring.add_key(TrustedKey {
+            public_key: pk,
+            fingerprint: [0; 32],
+            trust_level: level,
+        });
+        ring
+    }
+
+    #[test]
+    fn test_build_parse_roundtrip() {
+        let (manifest, files) = sample();
+        let data = build(&manifest, &files, Compression::Lz4, &SEED).unwrap();
+        let archive = VpkArchive::parse(&data).unwrap();
+        assert_eq!(archive.manifest().unwrap(), manifest);
+        assert_eq!(archive.files().unwrap(), files);
+        assert_eq!(
+            archive.verify(&ring_with(&SEED, TrustLevel::Developer)),
+            Ok(TrustLevel::Developer)
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering_and_unknown_keys() {
+        let (manifest, files) = sample();
+        let mut data = build(&manifest, &files, Compression::None, &SEED).unwrap();
+        let ring = ring_with(&SEED, TrustLevel::Core);
+
+        assert!(VpkArchive::parse(&data)
+            .unwrap()
+            .verify(&ring_with(&[9; 32], TrustLevel::Core))
+            .is_err());
+
+        let last_data_byte = data.len() - VPK_SIGNATURE_SIZE - 1;
+        data[last_data_byte] ^= 1;
+        assert!(VpkArchive::parse(&data).unwrap().verify(&ring).is_err());
+    }
+
+    #[test]
+    fn test_rejects_escaping_paths() {
+        let (manifest, mut files) = sample();
+        files[0].path = String::from("/usr/../etc/shadow");
+        assert!(build(&manifest, &files, Compression::None, &SEED).is_err());
+        assert!(VpkManifest::parse("[package]\nname = \"../x\"\nversion = \"1.0\"\n").is_err());
+    }
+
+    #[test]
+    fn test_load_trusted_keys() {
+        let mut ring = TrustedKeyRing::new();
+        let text = concat!(
+            "# comment\n",
+            "0101010101010101010101010101010101010101010101010101010101010101 core\n",
+            "02 developer\n",
+            "0303030303030303030303030303030303030303030303030303030303030303\n",
+        );
+        assert_eq!(load_trusted_keys(&mut ring, text), 2);
+        assert_eq!(
+            ring.find_key(&[1; 32]).map(|k| k.trust_level),
+            Some(TrustLevel::Core)
+        );
+        assert_eq!(
+            ring.find_key(&[3; 32]).map(|k| k.trust_level),
+            Some(TrustLevel::Community)
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Battery and AC adapter status
+//!
+//! Power supply drivers implement [`PowerSupply`] and [`register`] it.
+//! [`status`] combines all supplies for sysfs (`/sys/class/power_supply`)
+//! and the power IPC service the desktop panel queries. The timer tick
+//! polls the batteries through [`poll`] and asks init to power off once
+//! they run down to `battery.critical` percent while discharging.
+//!
+//! ACPI control method batteries (`PNP0C0A`, read through `_BIF`/`_BST`)
+//! and AC adapters (`ACPI0003`, `_PSR`) need an AML interpreter, which the
+//! kernel does not have yet. Until a driver registers a supply, the power
+//! source is reported as unknown, and treated as mains.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use spin::RwLock;
+
+use super::system::{PowerAction, RequestReason};
+
+crate::kernel_param! {
+    /// Battery percentage at which to power off while discharging (0: never).
+    pub static CRITICAL_PERCENT: u32 = 5, "battery.critical";
+}
+
+/// Time between two battery polls.
+const POLL_INTERVAL_MS: u64 = 5_000;
+
+/// Charging state of a battery, named as in Linux sysfs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum BatteryState {
+    /// The battery does not report it.
+    #[default]
+    Unknown = 0,
+    /// Charging from an external supply.
+    Charging = 1,
+    /// Powering the machine.
+    Discharging = 2,
+    /// On external power but not charging.
+    NotCharging = 3,
+    /// Fully charged.
+    Full = 4,
+}
+
+impl BatteryState {
+    /// Convert from the wire value.
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Charging,
+            2 => Self::Discharging,
+            3 => Self::NotCharging,
+            4 => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Name as in `/sys/class/power_supply/*/status`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "Unknown",
+            Self::Charging => "Charging",
+            Self::Discharging => "Discharging",
+            Self::NotCharging => "Not charging",
+            Self::Full => "Full",
+        }
+    }
+}
+
+/// Readings of one battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatteryReading {
+    /// Charging state.
+    pub state: BatteryState,
+    /// Remaining energy in mWh.
+    pub energy_now_mwh: u32,
+    /// Energy when fully charged in mWh.
+    pub energy_full_mwh: u32,
+    /// Charge or discharge rate in mW (0: unknown).
+    pub power_now_mw: u32,
+}
+
+/// A battery or AC adapter.
+///
+/// Both methods are called from the timer tick and must not block.
+pub trait PowerSupply: Send + Sync {
+    /// Supply name (e.g. `BAT0`, `AC`).
+    fn name(&self) -> &'static str;
+
+    /// For an AC adapter, whether it is plugged in; `None` for batteries.
+    fn online(&self) -> Option<bool>;
+
+    /// For a battery, its readings; `None` for adapters or when removed.
+    fn battery(&self) -> Option<BatteryReading>;
+}
+
+/// All power supplies combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PowerStatus {
+    /// Whether an AC adapter is plugged in; `None` without adapters.
+    pub ac_online: Option<bool>,
+    /// Whether at least one battery is present.
+    pub battery_present: bool,
+    /// Combined charging state of the batteries.
+    pub state: BatteryState,
+    /// Combined charge in percent.
+    pub capacity_percent: u8,
+    /// Remaining energy of all batteries in mWh.
+    pub energy_now_mwh: u32,
+    /// Energy of all batteries when fully charged in mWh.
+    pub energy_full_mwh: u32,
+    /// Combined charge or discharge rate in mW.
+    pub power_now_mw: u32,
+}
+
+impl PowerStatus {
+    /// Whether the machine runs on mains power. Without adapter readings,
+    /// unless a battery says it is discharging.
+    pub fn on_ac(&self) -> bool {
+        self.ac_online
+            .unwrap_or(!(self.battery_present && self.state == BatteryState::Discharging))
+    }
+}
+
+static SUPPLIES: RwLock<Vec<Box<dyn PowerSupply>>> = RwLock::new(Vec::new());
+
+/// Timestamp of the last poll.
+static LAST_POLL_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Set while a critical battery level has been reported.
+static CRITICAL_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Charge in percent, 0 when the full capacity is unknown.
+fn capacity_percent(energy_now_mwh: u32, energy_full_mwh: u32) -> u8 {
+    if energy_full_mwh == 0 {
+        return 0;
+    }
+    (energy_now_mwh as u64 * 100 / energy_full_mwh as u64).min(100) as u8
+}
+
+/// Combine adapter states and battery readings into one status.
+fn combine(
+    adapters: impl Iterator<Item = bool>,
+    batteries: impl Iterator<Item = BatteryReading>,
+) -> PowerStatus {
+    let mut status = PowerStatus::default();
+    for online in adapters {
+        status.ac_online = Some(status.ac_online.unwrap_or(false) || online);
+    }
+
+    let mut all_full = true;
+    for battery in batteries {
+        status.battery_present = true;
+        status.energy_now_mwh = status.energy_now_mwh.saturating_add(battery.energy_now_mwh);
+        status.energy_full_mwh = status
+            .energy_full_mwh
+            .saturating_add(battery.energy_full_mwh);
+        status.power_now_mw = status.power_now_mw.saturating_add(battery.power_now_mw);
+        all_full &= battery.state == BatteryState::Full;
+        // Discharging beats charging beats anything else.
+        status.state = match (status.state, battery.state) {
+            (BatteryState::Discharging, _) | (_, BatteryState::Discharging) => {
+                BatteryState::Discharging
+            }
+            (BatteryState::Charging, _) | (_, BatteryState::Charging) => BatteryState::Charging,
+            (BatteryState::Unknown, other) => other,
+            (current, _) => current,
+        };
+    }
+    if status.battery_present && all_full {
+        status.state = BatteryState::Full;
+    }
+    status.capacity_percent = capacity_percent(status.energy_now_mwh, status.energy_full_mwh);
+    status
+}
+
+/// Combine the readings of a set of supplies.
+fn status_of(supplies: &[Box<dyn PowerSupply>]) -> PowerStatus {
+    combine(
+        supplies.iter().filter_map(|s| s.online()),
+        supplies.iter().filter_map(|s| s.battery()),
+    )
+}
+
+/// Add a battery or AC adapter.
+pub fn register(supply: Box<dyn PowerSupply>) {
+    crate::println!("[BATTERY] Registered power supply {}", supply.name());
+    SUPPLIES.write().push(supply);
+}
+
+/// Current state of all power supplies.
+pub fn status() -> PowerStatus {
+    status_of(&SUPPLIES.read())
+}
+
+/// Ask init to power off when the batteries are critically low.
+///
+/// Called on every timer tick; acts at most once per `POLL_INTERVAL_MS`
+/// and skips the poll rather than wait for a lock.
+pub(crate) fn poll() {
+    let now = crate::arch::timer::get_timestamp_ms();
+    let last = LAST_POLL_MS.load(Ordering::Relaxed);
+    if last != 0 && now.saturating_sub(last) < POLL_INTERVAL_MS {
+        return;
+    }
+    LAST_POLL_MS.store(now.max(1), Ordering::Relaxed);
+
+    let Some(supplies) = SUPPLIES.try_read() else {
+        return;
+    };
+    if supplies.is_empty() {
+        return;
+    }
+    let status = status_of(&supplies);
+    drop(supplies);
+
+    let threshold = CRITICAL_PERCENT.get();
+    let critical = threshold != 0
+        && status.battery_present
+        && !status.on_ac()
+        && status.capacity_percent as u32 <= threshold;
+    if !critical {
+        // Re-arm once back on mains or charged above the threshold.
+        CRITICAL_REPORTED.store(false, Ordering::Relaxed);
+    } else if !CRITICAL_REPORTED.swap(true, Ordering::AcqRel) {
+        super::system::request(PowerAction::PowerOff, RequestReason::CriticalBattery);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(state: BatteryState, now: u32, full: u32) -> BatteryReading {
+        BatteryReading {
+            state,
+            energy_now_mwh: now,
+            energy_full_mwh: full,
+            power_now_mw: 0,
+        }
+    }
+
+    #[test]
+    fn test_capacity_percent() {
+        assert_eq!(capacity_percent(25_000, 50_000), 50);
+        assert_eq!(capacity_percent(50_000, 50_000), 100);
+        assert_eq!(capacity_percent(60_000, 50_000), 100);
+        assert_eq!(capacity_percent(10, 0), 0);
+    }
+
+    #[test]
+    fn test_combine_no_supplies() {
+        let status = combine(core::iter::empty(), core::iter::empty());
+        assert_eq!(status.ac_online, None);
+        assert!(!status.battery_present);
+        assert!(status.on_ac());
+    }
+
+    #[test]
+    fn test_combine_batteries() {
+        let status = combine(
+            [false].into_iter(),
+            [
+                reading(BatteryState::Discharging, 10_000, 40_000),
+                reading(BatteryState::Full, 40_000, 40_000),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(status.ac_online, Some(false));
+        assert!(status.battery_present);
+        assert_eq!(status.state, BatteryState::Discharging);
+        assert_eq!(status.capacity_percent, 62);
+        assert!(!status.on_ac());
+    }
+
+    #[test]
+    fn test_combine_full_and_charging() {
+        let full = combine(
+            core::iter::empty(),
+            [reading(BatteryState::Full, 100, 100)].into_iter(),
+        );
+        assert_eq!(full.state, BatteryState::Full);
+        assert!(full.on_ac());
+
+        let charging = combine(
+            [false, true].into_iter(),
+            [
+                reading(BatteryState::Full, 100, 100),
+                reading(BatteryState::Charging, 50, 100),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(charging.state, BatteryState::Charging);
+        assert_eq!(charging.ac_online, Some(true));
+    }
+
+    #[test]
+    fn test_battery_state_names() {
+        for value in 0..5 {
+            let state = BatteryState::from_u8(value);
+            assert_eq!(state as u8, value);
+            assert!(!state.name().is_empty());
+        }
+        assert_eq!(BatteryState::from_u8(200), BatteryState::Unknown);
+    }
+}
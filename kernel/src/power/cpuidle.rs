@@ -0,0 +1,305 @@
+//! CPU idle management (cpuidle)
+//!
+//! Every idle loop calls [`idle`] instead of halting the CPU itself. It
+//! predicts how long the CPU will stay idle from the recent past, enters the
+//! deepest C-state whose target residency and exit latency fit (see
+//! [`select_state`]), and accounts the time spent so the cpufreq governor can
+//! derive utilization from it.
+//!
+//! Each architecture needs something to end the idle period even when no
+//! device interrupt arrives:
+//! - x86_64: the local APIC timer tick. Until ticks are seen advancing, idle
+//!   polls rather than halting with nothing to wake it.
+//! - AArch64: the generic timer event stream, which wakes WFE periodically
+//!   without involving the timer interrupt.
+//! - RISC-V: a one-shot SBI timer armed around WFI (see [`super::enter_idle`]),
+//!   left masked in `sstatus` since no trap vector takes it yet.
+
+#[cfg(target_arch = "aarch64")]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{CState, CStateInfo};
+use crate::sched::smp::MAX_CPUS;
+
+crate::kernel_param! {
+    /// Deepest C-state idle may enter (0 polls, 1 only halts).
+    pub static MAX_CSTATE: u32 = 3, "cpuidle.max_cstate";
+}
+
+crate::kernel_param! {
+    /// Longest C-state exit latency idle may accept, in microseconds (0: any).
+    pub static MAX_LATENCY_US: u64 = 0, "cpuidle.max_latency_us";
+}
+
+/// Rate at which idle wakes up on its own to recheck for work, where the
+/// platform needs a wake-up armed for it.
+pub(crate) const IDLE_WAKE_HZ: u64 = 100;
+
+/// Minimum time between two governor utilization samples of one CPU.
+const SAMPLE_PERIOD_NS: u64 = 100_000_000;
+
+/// Spin iterations per call while polling (about a microsecond).
+const POLL_SPINS: u32 = 256;
+
+/// Per-CPU idle accounting.
+struct CpuIdleStats {
+    /// Total time spent idle
+    idle_ns: AtomicU64,
+    /// Start of the idle period in progress, 0 while running
+    idle_since_ns: AtomicU64,
+    /// Expected length of the next idle period (decaying average)
+    predicted_ns: AtomicU64,
+    /// Clock and cumulative idle time at the last utilization sample
+    sample_ns: AtomicU64,
+    sample_idle_ns: AtomicU64,
+}
+
+impl CpuIdleStats {
+    const fn new() -> Self {
+        Self {
+            idle_ns: AtomicU64::new(0),
+            idle_since_ns: AtomicU64::new(0),
+            predicted_ns: AtomicU64::new(0),
+            sample_ns: AtomicU64::new(0),
+            sample_idle_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Idle time including the idle period in progress, if any.
+    fn cumulative_idle_ns(&self, now: u64) -> u64 {
+        let since = self.idle_since_ns.load(Ordering::Acquire);
+        let current = if since == 0 {
+            0
+        } else {
+            now.saturating_sub(since)
+        };
+        self.idle_ns.load(Ordering::Acquire) + current
+    }
+}
+
+static STATS: [CpuIdleStats; MAX_CPUS] = [const { CpuIdleStats::new() }; MAX_CPUS];
+
+/// Whether the generic timer event stream is running on this CPU.
+#[cfg(target_arch = "aarch64")]
+static EVENT_STREAM: AtomicBool = AtomicBool::new(false);
+
+/// Idle statistics of the calling CPU. CPU IDs past `MAX_CPUS` share slots.
+fn cpu_stats() -> &'static CpuIdleStats {
+    &STATS[crate::sched::smp::current_cpu_id() as usize % MAX_CPUS]
+}
+
+/// Nanoseconds since boot from the hardware counter.
+fn now_ns() -> u64 {
+    let tps = crate::arch::timer::hw_ticks_per_second();
+    if tps == 0 {
+        return 0;
+    }
+    let ticks = crate::arch::timer::read_hw_timestamp();
+    (ticks as u128 * 1_000_000_000 / tps as u128) as u64
+}
+
+/// Set up the wake-up source idle relies on.
+pub(crate) fn init() {
+    #[cfg(all(target_arch = "aarch64", target_os = "none"))]
+    {
+        // Event stream: an event every 2^(EVNTI + 1) counter ticks. Pick the
+        // period closest below 1 / IDLE_WAKE_HZ that the 4-bit field allows.
+        let target = (crate::arch::timer::hw_ticks_per_second() / IDLE_WAKE_HZ).max(4);
+        let evnti = (target.ilog2() - 1).min(15) as u64;
+        // SAFETY: CNTKCTL_EL1 is accessible at EL1. Only EVNTEN (bit 2),
+        // EVNTDIR (bit 3) and EVNTI (bits 7:4) change; EL0 access bits are
+        // preserved. Events only end WFE early.
+        unsafe {
+            let mut cntkctl: u64;
+            core::arch::asm!("mrs {}, cntkctl_el1", out(reg) cntkctl);
+            cntkctl &= !0xFC;
+            cntkctl |= (evnti << 4) | (1 << 2);
+            core::arch::asm!("msr cntkctl_el1, {}", "isb", in(reg) cntkctl);
+        }
+        EVENT_STREAM.store(true, Ordering::Release);
+    }
+}
+
+/// Whether the CPU can halt and count on being woken up.
+fn wake_source_ready(_now: u64) -> bool {
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+    {
+        /// Treat the tick as stopped after this long without one.
+        const TICK_STALL_NS: u64 = 50_000_000;
+        static LAST_TICK: AtomicU64 = AtomicU64::new(0);
+        static LAST_TICK_NS: AtomicU64 = AtomicU64::new(0);
+
+        let ticks = crate::arch::timer::get_ticks();
+        if LAST_TICK.swap(ticks, Ordering::Relaxed) != ticks {
+            LAST_TICK_NS.store(_now, Ordering::Relaxed);
+            return true;
+        }
+        _now.saturating_sub(LAST_TICK_NS.load(Ordering::Relaxed)) < TICK_STALL_NS
+    }
+    #[cfg(all(target_arch = "aarch64", target_os = "none"))]
+    {
+        EVENT_STREAM.load(Ordering::Acquire)
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86_64", target_arch = "aarch64"),
+        target_os = "none"
+    )))]
+    {
+        true
+    }
+}
+
+/// Pick the C-state for an idle period expected to last `predicted_ns`.
+///
+/// Returns the deepest supported state no deeper than `max_state` whose exit
+/// latency is within `latency_limit_ns` (0: unlimited) and whose target
+/// residency the prediction covers. C1 needs no residency; C0 means poll.
+pub(crate) fn select_state(
+    table: &[CStateInfo; CState::COUNT],
+    predicted_ns: u64,
+    max_state: u32,
+    latency_limit_ns: u64,
+) -> CState {
+    let mut best = CState::C0;
+    for info in &table[1..] {
+        if !info.supported || info.state as u32 > max_state {
+            continue;
+        }
+        if latency_limit_ns != 0 && info.exit_latency_ns > latency_limit_ns {
+            continue;
+        }
+        if info.state == CState::C1 || info.target_residency_ns <= predicted_ns {
+            best = info.state;
+        }
+    }
+    best
+}
+
+/// Fold an observed idle period into the prediction: 7/8 history, 1/8 new.
+fn predict(previous_ns: u64, observed_ns: u64) -> u64 {
+    previous_ns - previous_ns / 8 + observed_ns / 8
+}
+
+/// Busy share of a sampling window, in percent.
+fn utilization(window_ns: u64, idle_ns: u64) -> u32 {
+    if window_ns == 0 {
+        return 0;
+    }
+    let busy = window_ns - idle_ns.min(window_ns);
+    (busy as u128 * 100 / window_ns as u128) as u32
+}
+
+/// Idle the calling CPU until an interrupt or the next idle wake-up.
+///
+/// Callers loop around this and recheck for work each time it returns.
+pub fn idle() {
+    let stats = cpu_stats();
+    let start = now_ns();
+
+    let state = if !wake_source_ready(start) {
+        CState::C0
+    } else if !super::is_initialized() {
+        CState::C1
+    } else {
+        select_state(
+            &super::get_supported_cstates(),
+            stats.predicted_ns.load(Ordering::Relaxed),
+            MAX_CSTATE.get(),
+            MAX_LATENCY_US.get().saturating_mul(1_000),
+        )
+    };
+
+    stats.idle_since_ns.store(start.max(1), Ordering::Release);
+    if state == CState::C0 {
+        for _ in 0..POLL_SPINS {
+            core::hint::spin_loop();
+        }
+    } else {
+        super::enter_idle(state);
+    }
+    let end = now_ns();
+    // Clear the marker first: a sample taken in between undercounts idle
+    // time, which errs towards a higher frequency.
+    stats.idle_since_ns.store(0, Ordering::Release);
+
+    let slept = end.saturating_sub(start);
+    stats.idle_ns.fetch_add(slept, Ordering::AcqRel);
+    let previous = stats.predicted_ns.load(Ordering::Relaxed);
+    stats
+        .predicted_ns
+        .store(predict(previous, slept), Ordering::Relaxed);
+}
+
+/// Busy share of the calling CPU since its previous sample, in percent.
+///
+/// Returns `None` on the first call and until `SAMPLE_PERIOD_NS` has passed
+/// since the previous sample. Lock-free, for use from the timer tick.
+pub(crate) fn sample_utilization() -> Option<u32> {
+    let stats = cpu_stats();
+    let now = now_ns();
+    let last = stats.sample_ns.load(Ordering::Relaxed);
+    if last != 0 && now.saturating_sub(last) < SAMPLE_PERIOD_NS {
+        return None;
+    }
+
+    let idle = stats.cumulative_idle_ns(now);
+    let last_idle = stats.sample_idle_ns.swap(idle, Ordering::Relaxed);
+    stats.sample_ns.store(now.max(1), Ordering::Relaxed);
+    if last == 0 {
+        return None;
+    }
+    Some(utilization(
+        now.saturating_sub(last),
+        idle.saturating_sub(last_idle),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::DEFAULT_CSTATE_TABLE;
+
+    #[test]
+    fn test_select_state_by_residency() {
+        let table = DEFAULT_CSTATE_TABLE;
+        assert_eq!(select_state(&table, 0, 3, 0), CState::C1);
+        assert_eq!(select_state(&table, 499_999, 3, 0), CState::C1);
+        assert_eq!(select_state(&table, 500_000, 3, 0), CState::C2);
+        assert_eq!(select_state(&table, 10_000_000, 3, 0), CState::C3);
+    }
+
+    #[test]
+    fn test_select_state_limits() {
+        let table = DEFAULT_CSTATE_TABLE;
+        assert_eq!(select_state(&table, 10_000_000, 2, 0), CState::C2);
+        assert_eq!(select_state(&table, 10_000_000, 1, 0), CState::C1);
+        assert_eq!(select_state(&table, 10_000_000, 0, 0), CState::C0);
+        assert_eq!(select_state(&table, 10_000_000, 3, 100_000), CState::C2);
+        assert_eq!(select_state(&table, 10_000_000, 3, 500), CState::C0);
+    }
+
+    #[test]
+    fn test_select_state_skips_unsupported() {
+        let mut table = DEFAULT_CSTATE_TABLE;
+        table[2].supported = false;
+        table[3].supported = false;
+        assert_eq!(select_state(&table, 10_000_000, 3, 0), CState::C1);
+    }
+
+    #[test]
+    fn test_predict_decays() {
+        assert_eq!(predict(0, 8_000), 1_000);
+        assert_eq!(predict(8_000, 8_000), 8_000);
+        assert_eq!(predict(8_000, 0), 7_000);
+    }
+
+    #[test]
+    fn test_utilization() {
+        assert_eq!(utilization(1_000, 0), 100);
+        assert_eq!(utilization(1_000, 250), 75);
+        assert_eq!(utilization(1_000, 1_000), 0);
+        assert_eq!(utilization(1_000, 5_000), 0);
+        assert_eq!(utilization(0, 0), 0);
+    }
+}
@@ -6,12 +6,22 @@
 //! - OnDemand governor: automatic frequency scaling based on CPU utilization
 //!
 //! Architecture support:
-//! - x86_64: HLT (C1), MWAIT (C2/C3), IA32_PERF_CTL MSR for P-states
-//! - AArch64: WFI for idle states
-//! - RISC-V: WFI for idle states
+//! - x86_64: HLT (C1), MWAIT (C2/C3), P-states through the cpufreq driver
+//! - AArch64: WFE for idle states, woken by the generic timer event stream
+//! - RISC-V: WFI for idle states, woken by a one-shot SBI timer
+//!
+//! Idle loops enter C-states through [`cpuidle`]; the governor is sampled
+//! from the timer tick, which also polls [`thermal`] zones (capping the
+//! P-state when they run hot) and [`battery`] levels. Powering off,
+//! rebooting and suspending the whole machine live in [`system`].
 
 #![allow(dead_code)]
 
+pub mod battery;
+pub mod cpuidle;
+pub mod system;
+pub mod thermal;
+
 use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 
 use spin::RwLock;
@@ -150,6 +160,30 @@ pub(crate) enum Governor {
     PowerSave,
 }
 
+impl Governor {
+    /// Names of all governors, as listed in sysfs.
+    pub(crate) const AVAILABLE: &'static str = "ondemand performance powersave";
+
+    /// Name used in sysfs.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Governor::OnDemand => "ondemand",
+            Governor::Performance => "performance",
+            Governor::PowerSave => "powersave",
+        }
+    }
+
+    /// Parse a sysfs governor name.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ondemand" => Some(Governor::OnDemand),
+            "performance" => Some(Governor::Performance),
+            "powersave" => Some(Governor::PowerSave),
+            _ => None,
+        }
+    }
+}
+
 /// Utilization threshold above which governor selects max P-state (percent)
 const GOVERNOR_HIGH_THRESHOLD: u32 = 80;
 /// Utilization threshold below which governor selects min P-state (percent)
@@ -194,6 +228,15 @@ fn ondemand_target(utilization_percent: u32, num_pstates: usize) -> usize {
     }
 }
 
+/// Target P-state index for `governor` at the given utilization.
+fn governor_target(governor: Governor, utilization_percent: u32, num_pstates: usize) -> usize {
+    match governor {
+        Governor::OnDemand => ondemand_target(utilization_percent, num_pstates),
+        Governor::Performance => num_pstates.saturating_sub(1),
+        Governor::PowerSave => 0,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Global state
 // ---------------------------------------------------------------------------
@@ -239,6 +282,9 @@ static CURRENT_CSTATE: AtomicU8 = AtomicU8::new(0); // C0
 /// Current P-state index (atomic for lock-free read)
 static CURRENT_PSTATE: AtomicUsize = AtomicUsize::new(0);
 
+/// Highest P-state index the thermal policy allows (`usize::MAX`: no limit)
+static THERMAL_CAP: AtomicUsize = AtomicUsize::new(usize::MAX);
+
 // ---------------------------------------------------------------------------
 // Initialization
 // ---------------------------------------------------------------------------
@@ -246,9 +292,20 @@ static CURRENT_PSTATE: AtomicUsize = AtomicUsize::new(0);
 /// Initialize the power management subsystem.
 ///
 /// Detects hardware capabilities (MWAIT, P-state support) and populates
-/// the C-state and P-state tables. Falls back to hardcoded defaults when
-/// ACPI tables are not available.
+/// the C-state and P-state tables. On x86_64 the P-states span the range
+/// the cpufreq driver reports, and are left empty without P-state control;
+/// elsewhere the hardcoded defaults are used.
 pub(crate) fn init() -> Result<(), KernelError> {
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+    let freq_range_khz = {
+        use crate::arch::x86_64::cpufreq;
+        let _ = cpufreq::cpufreq_init();
+        (
+            cpufreq::cpufreq_get_min_frequency(),
+            cpufreq::cpufreq_get_max_frequency(),
+        )
+    };
+
     let mut state = POWER_STATE.write();
     if state.initialized {
         return Err(KernelError::AlreadyExists {
@@ -262,25 +319,49 @@ pub(crate) fn init() -> Result<(), KernelError> {
 
     // Update C-state support based on hardware
     // C0 and C1 (HLT/WFI) are always supported
-    // C2/C3 require MWAIT on x86_64
+    // C2/C3 require MWAIT on x86_64; other architectures only have
+    // WFI/WFE until PSCI CPU_SUSPEND or SBI HSM suspend is used
     state.cstates = DEFAULT_CSTATE_TABLE;
-    #[cfg(target_arch = "x86_64")]
+    state.cstates[2].supported = state.mwait_supported; // C2
+    state.cstates[3].supported = state.mwait_supported; // C3
+
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
     {
-        state.cstates[2].supported = state.mwait_supported; // C2
-        state.cstates[3].supported = state.mwait_supported; // C3
+        let (min_khz, max_khz) = freq_range_khz;
+        state.num_pstates = 0;
+        if min_khz != 0 && min_khz < max_khz {
+            let steps = ((max_khz - min_khz) / 100_000 + 1).min(MAX_PSTATES as u64);
+            for i in 0..steps {
+                let khz = min_khz + (max_khz - min_khz) * i / (steps - 1);
+                // Voltage and power are not reported by the hardware.
+                state.pstates[i as usize] = PState {
+                    frequency_mhz: (khz / 1000) as u32,
+                    voltage_mv: 0,
+                    power_mw: 0,
+                };
+            }
+            state.num_pstates = steps as usize;
+        }
     }
-
-    // Populate P-state table from defaults
-    // (In a full implementation, this would parse ACPI _PSS objects)
-    let defaults = &DEFAULT_PSTATE_TABLE;
-    for (i, pstate) in defaults.iter().enumerate() {
-        state.pstates[i] = *pstate;
+    #[cfg(not(all(target_arch = "x86_64", target_os = "none")))]
+    {
+        // Populate P-state table from defaults
+        // (In a full implementation, this would parse ACPI _PSS objects)
+        let defaults = &DEFAULT_PSTATE_TABLE;
+        for (i, pstate) in defaults.iter().enumerate() {
+            state.pstates[i] = *pstate;
+        }
+        state.num_pstates = defaults.len();
     }
-    state.num_pstates = defaults.len();
 
     state.initialized = true;
+    // Firmware hands over at the highest non-turbo frequency.
     CURRENT_CSTATE.store(CState::C0 as u8, Ordering::Release);
-    CURRENT_PSTATE.store(0, Ordering::Release);
+    CURRENT_PSTATE.store(state.num_pstates.saturating_sub(1), Ordering::Release);
+    drop(state);
+
+    cpuidle::init();
+    thermal::init();
 
     Ok(())
 }
@@ -415,11 +496,12 @@ fn x86_mwait(hint: u32) {
 
 #[cfg(all(target_arch = "aarch64", target_os = "none"))]
 fn arch_enter_idle(_cstate: CState) {
-    // AArch64: WFI (Wait For Interrupt) for all idle states.
-    // Deeper C-states would require platform-specific PSCI calls.
-    // SAFETY: WFI halts the core until an interrupt occurs.
+    // AArch64: WFE (Wait For Event) for all idle states, so the generic
+    // timer event stream set up by cpuidle::init ends it as well as any
+    // interrupt. Deeper C-states would require PSCI CPU_SUSPEND.
+    // SAFETY: WFE halts the core until an event or interrupt occurs.
     unsafe {
-        core::arch::asm!("wfi", options(nomem, nostack, preserves_flags));
+        core::arch::asm!("wfe", options(nomem, nostack, preserves_flags));
     }
 }
 
@@ -427,11 +509,26 @@ fn arch_enter_idle(_cstate: CState) {
 
 #[cfg(all(target_arch = "riscv64", target_os = "none"))]
 fn arch_enter_idle(_cstate: CState) {
+    use crate::arch::riscv::{sbi, timer};
+
+    /// Supervisor timer interrupt enable bit in `sie`.
+    const SIE_STIE: usize = 1 << 5;
+
     // RISC-V: WFI (Wait For Interrupt) for all idle states.
     // Deeper states would require SBI HSM extension calls.
-    // SAFETY: WFI halts the hart until an interrupt occurs.
+    // No trap vector takes the timer interrupt yet, so keep sstatus.SIE
+    // clear and only enable it in `sie`: a pending enabled interrupt ends
+    // WFI without being taken.
+    let _guard = crate::arch::disable_interrupts();
+    let interval = crate::arch::timer::hw_ticks_per_second() / cpuidle::IDLE_WAKE_HZ;
+    sbi::set_timer(timer::read_time() + interval);
+    // SAFETY: STIE is only set while sstatus.SIE is clear, so the timer
+    // interrupt wakes WFI but is never delivered; it is cleared again before
+    // the guard restores SIE. WFI halts the hart until then.
     unsafe {
+        core::arch::asm!("csrs sie, {}", in(reg) SIE_STIE, options(nomem, nostack));
         core::arch::asm!("wfi", options(nomem, nostack, preserves_flags));
+        core::arch::asm!("csrc sie, {}", in(reg) SIE_STIE, options(nomem, nostack));
     }
 }
 
@@ -446,10 +543,6 @@ fn arch_enter_idle(_cstate: CState) {
 // P-state transitions
 // ---------------------------------------------------------------------------
 
-/// MSR address for IA32_PERF_CTL (P-state selection)
-#[cfg(all(target_arch = "x86_64", target_os = "none"))]
-const IA32_PERF_CTL: u32 = 0x199;
-
 /// Set the CPU frequency to the given P-state index.
 ///
 /// Index 0 is the lowest frequency; index `num_pstates - 1` is the highest.
@@ -470,7 +563,9 @@ pub(crate) fn set_frequency(pstate_index: usize) -> Result<(), KernelError> {
     let pstate = state.pstates[pstate_index];
     drop(state); // Release lock before hardware access
 
-    arch_set_pstate(pstate_index, &pstate);
+    if !arch_set_pstate(&pstate) {
+        return Err(KernelError::WouldBlock);
+    }
     CURRENT_PSTATE.store(pstate_index, Ordering::Release);
 
     Ok(())
@@ -478,24 +573,22 @@ pub(crate) fn set_frequency(pstate_index: usize) -> Result<(), KernelError> {
 
 // --- x86_64 bare-metal P-state write ---
 
+/// Switch to `pstate`. Returns `false` if the driver was busy; never waits,
+/// as the governor calls this from the timer interrupt.
 #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-fn arch_set_pstate(index: usize, _pstate: &PState) {
-    // IA32_PERF_CTL bits [15:0] select the target P-state.
-    // The actual encoding is platform-specific; we use the index directly
-    // as a simplified mapping. A production implementation would use the
-    // ratio field from ACPI _PSS.
-    let value = index as u64 & 0xFFFF;
-    crate::arch::x86_64::msr::wrmsr(IA32_PERF_CTL, value);
+fn arch_set_pstate(pstate: &PState) -> bool {
+    crate::arch::x86_64::cpufreq::cpufreq_try_set_frequency(pstate.frequency_mhz as u64 * 1000)
 }
 
 // --- Non-x86_64 / host-target stub ---
 
 #[cfg(not(all(target_arch = "x86_64", target_os = "none")))]
-fn arch_set_pstate(_index: usize, _pstate: &PState) {
+fn arch_set_pstate(_pstate: &PState) -> bool {
     // P-state control is architecture-specific.
     // AArch64: SCMI or platform firmware calls would go here.
     // RISC-V: No standard P-state interface; vendor-specific.
     // Host: No-op for testing.
+    true
 }
 
 // ---------------------------------------------------------------------------
@@ -521,11 +614,7 @@ pub(crate) fn governor_tick(utilization_percent: u32) -> Result<(), KernelError>
         return Ok(());
     }
 
-    let target = match governor {
-        Governor::OnDemand => ondemand_target(utilization_percent, num),
-        Governor::Performance => num - 1,
-        Governor::PowerSave => 0,
-    };
+    let target = governor_target(governor, utilization_percent, num).min(thermal_cap());
 
     let current = CURRENT_PSTATE.load(Ordering::Acquire);
     if target != current {
@@ -535,14 +624,67 @@ pub(crate) fn governor_tick(utilization_percent: u32) -> Result<(), KernelError>
     Ok(())
 }
 
+/// Sample this CPU's utilization and apply the governor's choice.
+///
+/// Called on every timer tick; acts at most once per cpuidle sampling
+/// period. Runs in interrupt context, so it skips the sample rather than
+/// wait for a lock.
+pub(crate) fn governor_sample() {
+    let Some(utilization) = cpuidle::sample_utilization() else {
+        return;
+    };
+    let Some(state) = POWER_STATE.try_read() else {
+        return;
+    };
+    if !state.initialized || state.num_pstates == 0 {
+        return;
+    }
+    let target = governor_target(state.governor, utilization, state.num_pstates).min(thermal_cap());
+    let pstate = state.pstates[target];
+    drop(state);
+
+    if target != CURRENT_PSTATE.load(Ordering::Acquire) && arch_set_pstate(&pstate) {
+        CURRENT_PSTATE.store(target, Ordering::Release);
+    }
+}
+
 /// Set the active governor policy.
+///
+/// Performance and PowerSave take effect immediately; OnDemand on the next
+/// governor sample.
 pub(crate) fn set_governor(gov: Governor) -> Result<(), KernelError> {
     let mut state = POWER_STATE.write();
     if !state.initialized {
         return Err(KernelError::NotInitialized { subsystem: "power" });
     }
     state.governor = gov;
-    Ok(())
+    let num = state.num_pstates;
+    drop(state);
+
+    if num == 0 || gov == Governor::OnDemand {
+        return Ok(());
+    }
+    set_frequency(governor_target(gov, 0, num).min(thermal_cap()))
+}
+
+/// Limit the governor to P-states up to `index` (`usize::MAX`: no limit).
+/// Takes effect on the next governor sample.
+pub(crate) fn set_thermal_cap(index: usize) {
+    THERMAL_CAP.store(index, Ordering::Release);
+}
+
+/// Highest P-state index the governor may currently pick.
+pub(crate) fn thermal_cap() -> usize {
+    THERMAL_CAP.load(Ordering::Acquire)
+}
+
+/// Periodic power management work, called on every timer tick: governor
+/// sampling and thermal and battery polling. Each rate-limits itself and
+/// none waits for a lock.
+pub(crate) fn timer_tick() {
+    governor_sample();
+    thermal::poll();
+    battery::poll();
 }
 
 /// Get the active governor policy.
@@ -581,6 +723,36 @@ pub(crate) fn get_supported_pstates() -> ([PState; MAX_PSTATES], usize) {
     (state.pstates, state.num_pstates)
 }
 
+/// Frequency of P-state `index` in kHz, 0 when there is no such state.
+fn pstate_khz(index: usize) -> u64 {
+    let state = POWER_STATE.read();
+    if index < state.num_pstates {
+        state.pstates[index].frequency_mhz as u64 * 1000
+    } else {
+        0
+    }
+}
+
+/// Current CPU frequency in kHz, 0 without frequency scaling.
+pub(crate) fn current_frequency_khz() -> u64 {
+    pstate_khz(get_current_pstate())
+}
+
+/// Lowest and highest selectable frequencies in kHz, 0 without frequency
+/// scaling.
+pub(crate) fn frequency_range_khz() -> (u64, u64) {
+    (
+        pstate_khz(0),
+        pstate_khz(get_pstate_count().saturating_sub(1)),
+    )
+}
+
+/// Number of P-states, or `None` if the state is locked. For interrupt
+/// context.
+fn try_pstate_count() -> Option<usize> {
+    POWER_STATE.try_read().map(|state| state.num_pstates)
+}
+
 /// Get the number of supported P-states.
 pub(crate) fn get_pstate_count() -> usize {
     POWER_STATE.read().num_pstates
@@ -728,6 +900,27 @@ mod tests {
         // If we get here, it did not halt
     }
 
+    #[test]
+    fn test_governor_target() {
+        assert_eq!(governor_target(Governor::Performance, 0, 4), 3);
+        assert_eq!(governor_target(Governor::PowerSave, 100, 4), 0);
+        assert_eq!(governor_target(Governor::OnDemand, 100, 4), 3);
+        assert_eq!(governor_target(Governor::Performance, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_governor_names() {
+        for gov in [
+            Governor::OnDemand,
+            Governor::Performance,
+            Governor::PowerSave,
+        ] {
+            assert_eq!(Governor::from_name(gov.name()), Some(gov));
+            assert!(Governor::AVAILABLE.contains(gov.name()));
+        }
+        assert_eq!(Governor::from_name("schedutil"), None);
+    }
+
     #[test]
     fn test_governor_variants() {
         // Verify governor enum values
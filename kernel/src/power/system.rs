@@ -0,0 +1,424 @@
+//! System power control: power off, reboot, halt and suspend
+//!
+//! Drives each platform's firmware interface:
+//! - x86_64: ACPI S5 for power off; the FADT reset register, then the 0xCF9
+//!   reset control register, the 8042 keyboard controller and finally a triple
+//!   fault for reset; ACPI S3 for suspend
+//! - AArch64: PSCI SYSTEM_OFF / SYSTEM_RESET over the conduit named by the
+//!   device tree `/psci` node (HVC unless it says `smc`)
+//! - RISC-V: the SBI system reset extension (SRST), falling back to the legacy
+//!   SBI shutdown call
+//!
+//! [`init`] resolves everything that needs a lock (ACPI tables, the device
+//! tree) up front, so [`reset`] can run from interrupt context. User space
+//! reaches this through `SYS_REBOOT`, which init calls once it has stopped
+//! its services. Suspend to RAM is only offered when booted with `suspend`.
+//!
+//! Events the kernel sees first (the power button, a critical temperature or
+//! battery level) are not acted on directly: [`request`] records them and
+//! init collects them through `SYS_REBOOT`, so services get shut down in
+//! order.
+
+#[cfg(target_arch = "aarch64")]
+use core::sync::atomic::AtomicBool;
+#[cfg(target_arch = "x86_64")]
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize};
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use crate::error::KernelError;
+
+crate::kernel_param! {
+    /// Allow suspend to RAM (ACPI S3) through SYS_REBOOT and /sys/power/state.
+    pub static SUSPEND: bool = false, "suspend";
+}
+
+/// What to do with the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerAction {
+    /// Turn the machine off.
+    PowerOff,
+    /// Reset the machine.
+    Reboot,
+    /// Stop all CPUs without powering off.
+    Halt,
+    /// Suspend to RAM and resume.
+    Suspend,
+}
+
+/// Why the kernel asked for a power action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RequestReason {
+    /// The power button was pressed.
+    PowerButton = 1,
+    /// A thermal zone reached its critical trip point.
+    CriticalTemperature = 2,
+    /// The battery is discharging and nearly empty.
+    CriticalBattery = 3,
+}
+
+impl RequestReason {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::PowerButton),
+            2 => Some(Self::CriticalTemperature),
+            3 => Some(Self::CriticalBattery),
+            _ => None,
+        }
+    }
+
+    /// Human-readable description for the log.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::PowerButton => "power button pressed",
+            Self::CriticalTemperature => "critical temperature",
+            Self::CriticalBattery => "critical battery level",
+        }
+    }
+}
+
+/// Pending kernel request: action in the low byte (0: none), reason above.
+static PENDING_REQUEST: AtomicU16 = AtomicU16::new(0);
+
+/// Reset register from the FADT: address space (`RESET_SPACE_NONE` when
+/// absent), port or mapped address, and the value to write.
+#[cfg(target_arch = "x86_64")]
+const RESET_SPACE_NONE: u8 = 0xFF;
+#[cfg(target_arch = "x86_64")]
+static RESET_SPACE: AtomicU8 = AtomicU8::new(RESET_SPACE_NONE);
+#[cfg(target_arch = "x86_64")]
+static RESET_ADDRESS: AtomicUsize = AtomicUsize::new(0);
+#[cfg(target_arch = "x86_64")]
+static RESET_VALUE: AtomicU8 = AtomicU8::new(0);
+
+/// Whether the platform has an 8042 keyboard controller to reset through.
+#[cfg(target_arch = "x86_64")]
+static HAS_8042: AtomicBool = AtomicBool::new(true);
+
+/// PSCI calls go through SMC instead of HVC.
+#[cfg(target_arch = "aarch64")]
+static PSCI_SMC: AtomicBool = AtomicBool::new(false);
+
+/// PSCI function IDs (SMC32 calling convention).
+#[cfg(target_arch = "aarch64")]
+const PSCI_SYSTEM_OFF: u32 = 0x8400_0008;
+#[cfg(target_arch = "aarch64")]
+const PSCI_SYSTEM_RESET: u32 = 0x8400_0009;
+
+/// Look up the platform's power off and reset mechanisms.
+///
+/// Call once ACPI tables are parsed (x86_64) or the device tree is saved
+/// (AArch64). Until then the built-in defaults for QEMU are used.
+pub fn init() {
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+    {
+        if let Some(fadt) = crate::arch::x86_64::acpi::fadt() {
+            HAS_8042.store(fadt.has_8042(), Ordering::Relaxed);
+            if let Some(reg) = fadt.reset_reg {
+                let address = match reg.address_space {
+                    0 => crate::arch::x86_64::msr::phys_to_virt(reg.address as usize),
+                    1 => Some(reg.address as usize),
+                    _ => None,
+                };
+                if let Some(address) = address {
+                    RESET_ADDRESS.store(address, Ordering::Relaxed);
+                    RESET_VALUE.store(fadt.reset_value, Ordering::Relaxed);
+                    RESET_SPACE.store(reg.address_space, Ordering::Release);
+                }
+            }
+        }
+        if crate::arch::x86_64::acpi::is_initialized() {
+            if let Err(_e) = crate::arch::x86_64::acpi_pm::acpi_pm_init() {
+                crate::println!("[POWER] ACPI power management unavailable: {:?}", _e);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        let smc = crate::utils::fdt::with_blob(|fdt| {
+            fdt.nodes()
+                .find(|node| node.compatible().any(|c| c.starts_with("arm,psci")))
+                .and_then(|node| node.str_property("method"))
+                == Some("smc")
+        })
+        .unwrap_or(false);
+        PSCI_SMC.store(smc, Ordering::Relaxed);
+    }
+
+    crate::println!(
+        "[POWER] System power control ready (suspend {})",
+        if SUSPEND.get() { "enabled" } else { "disabled" }
+    );
+}
+
+/// Ask init to carry out `action`, for events noticed by the kernel.
+///
+/// Lock-free, so it can be called from interrupt context. A request that
+/// init has not collected yet is kept: the first one wins.
+pub fn request(action: PowerAction, reason: RequestReason) {
+    let code = action_code(action) as u16 | ((reason as u16) << 8);
+    let _ = PENDING_REQUEST.compare_exchange(0, code, Ordering::AcqRel, Ordering::Acquire);
+}
+
+/// Collect the pending request, if any, logging its reason.
+pub fn take_request() -> Option<PowerAction> {
+    let code = PENDING_REQUEST.swap(0, Ordering::AcqRel);
+    let action = action_from_code((code & 0xFF) as u8)?;
+    if let Some(_reason) = RequestReason::from_u8((code >> 8) as u8) {
+        crate::println!(
+            "[POWER] {}: asking init to {:?}",
+            _reason.description(),
+            action
+        );
+    }
+    Some(action)
+}
+
+fn action_code(action: PowerAction) -> u8 {
+    match action {
+        PowerAction::PowerOff => 1,
+        PowerAction::Reboot => 2,
+        PowerAction::Halt => 3,
+        PowerAction::Suspend => 4,
+    }
+}
+
+fn action_from_code(code: u8) -> Option<PowerAction> {
+    match code {
+        1 => Some(PowerAction::PowerOff),
+        2 => Some(PowerAction::Reboot),
+        3 => Some(PowerAction::Halt),
+        4 => Some(PowerAction::Suspend),
+        _ => None,
+    }
+}
+
+/// Flush every mounted filesystem, logging the ones that fail.
+pub fn sync_filesystems() {
+    #[cfg(feature = "alloc")]
+    if let Some(vfs) = crate::fs::try_get_vfs() {
+        if let Err(_e) = vfs.read().sync() {
+            crate::println!("[POWER] Filesystem sync failed: {:?}", _e);
+        }
+    }
+}
+
+/// Sync and unmount every filesystem but the root, deepest first, then
+/// sync the root.
+pub fn unmount_filesystems() {
+    #[cfg(feature = "alloc")]
+    if let Some(vfs) = crate::fs::try_get_vfs() {
+        let vfs = vfs.write();
+        if let Err(_e) = vfs.sync() {
+            crate::println!("[POWER] Filesystem sync failed: {:?}", _e);
+        }
+        let mut mounts: alloc::vec::Vec<_> = vfs
+            .list_mounts()
+            .into_iter()
+            .map(|(path, _, _)| path)
+            .filter(|path| path != "/")
+            .collect();
+        mounts.sort_by_key(|path| core::cmp::Reverse(path.len()));
+        for path in mounts {
+            if let Err(_e) = vfs.unmount(&path) {
+                crate::println!("[POWER] Cannot unmount {}: {:?}", path, _e);
+            }
+        }
+    }
+}
+
+/// Carry out `action`. Only [`PowerAction::Suspend`] returns, after resume
+/// or when suspend is unavailable.
+pub fn perform(action: PowerAction) -> Result<(), KernelError> {
+    match action {
+        PowerAction::PowerOff => power_off(),
+        PowerAction::Reboot => reboot(),
+        PowerAction::Halt => halt(),
+        PowerAction::Suspend => suspend(),
+    }
+}
+
+/// Unmount filesystems and turn the machine off. Halts if that fails.
+pub fn power_off() -> ! {
+    crate::println!("[POWER] Powering off");
+    unmount_filesystems();
+
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+    {
+        if crate::arch::x86_64::acpi_pm::is_initialized() {
+            // Halts rather than returning if the write does not take.
+            let _ = crate::arch::x86_64::acpi_pm::acpi_shutdown_s5();
+        }
+        // QEMU's isa-debug-exit device, when configured
+        // SAFETY: port 0xF4 is unused on real hardware; under QEMU with
+        // isa-debug-exit the write terminates the VM.
+        unsafe {
+            crate::arch::x86_64::outl(0xF4, 0);
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    psci_call(PSCI_SYSTEM_OFF);
+
+    #[cfg(target_arch = "riscv64")]
+    {
+        use crate::arch::riscv::sbi;
+        sbi::system_reset(sbi::SBI_RESET_TYPE_SHUTDOWN, sbi::SBI_RESET_REASON_NONE);
+        sbi::legacy_shutdown();
+    }
+
+    crate::println!("[POWER] Power off did not take effect, halting");
+    crate::arch::halt()
+}
+
+/// Unmount filesystems and reset the machine. Halts if that fails.
+pub fn reboot() -> ! {
+    crate::println!("[POWER] Rebooting");
+    unmount_filesystems();
+    reset();
+    crate::println!("[POWER] Reset did not take effect, halting");
+    crate::arch::halt()
+}
+
+/// Sync filesystems and stop the CPU for good.
+pub fn halt() -> ! {
+    crate::println!("[POWER] System halted");
+    sync_filesystems();
+    crate::arch::halt()
+}
+
+/// Suspend to RAM, returning once the machine has resumed.
+///
+/// Devices bound in the driver framework are suspended children first
+/// before the machine sleeps and resumed parents first after; if one of
+/// them can't be suspended, the machine stays up and its error is returned.
+///
+/// Fails with `OperationNotSupported` unless booted with `suspend`, and on
+/// platforms without ACPI S3.
+pub fn suspend() -> Result<(), KernelError> {
+    if !SUSPEND.get() {
+        return Err(KernelError::OperationNotSupported {
+            operation: "suspend (boot with `suspend` to enable)",
+        });
+    }
+    sync_filesystems();
+
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+    {
+        let devices = crate::services::driver_framework::try_get_driver_framework();
+        if let Some(framework) = devices {
+            framework.suspend_devices()?;
+        }
+
+        // The hardware watchdog keeps counting while the timer tick that
+        // kicks it is stopped
+        crate::drivers::watchdog::stop_hardware();
+        let result = crate::arch::x86_64::acpi_pm::acpi_suspend_s3();
+        crate::drivers::watchdog::start_hardware();
+
+        if let Some(framework) = devices {
+            if let Err(_e) = framework.resume_devices() {
+                crate::println!("[POWER] Not all devices resumed: {:?}", _e);
+            }
+        }
+        result
+    }
+    #[cfg(not(all(target_arch = "x86_64", target_os = "none")))]
+    {
+        Err(KernelError::OperationNotSupported {
+            operation: "suspend to RAM on this platform",
+        })
+    }
+}
+
+/// Reset the machine right away, without touching filesystems. Returns only
+/// if no reset mechanism worked.
+///
+/// Uses only atomics, so the watchdog can call it from a timer interrupt.
+pub fn reset() {
+    #[cfg(target_arch = "x86_64")]
+    x86_reset();
+
+    #[cfg(target_arch = "aarch64")]
+    psci_call(PSCI_SYSTEM_RESET);
+
+    #[cfg(target_arch = "riscv64")]
+    {
+        use crate::arch::riscv::sbi;
+        sbi::system_reset(sbi::SBI_RESET_TYPE_COLD_REBOOT, sbi::SBI_RESET_REASON_NONE);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn x86_reset() {
+    use x86_64::instructions::port::Port;
+
+    /// Reset control register (PIIX/ICH and QEMU); 0x06 = full hard reset.
+    const RESET_CONTROL: u16 = 0xCF9;
+
+    let value = RESET_VALUE.load(Ordering::Relaxed);
+    let address = RESET_ADDRESS.load(Ordering::Relaxed);
+    match RESET_SPACE.load(Ordering::Acquire) {
+        // SAFETY: the address is the FADT reset register, mapped through
+        // the physical memory window by init(); writing the FADT reset
+        // value to it is how ACPI defines a system reset.
+        0 => unsafe { core::ptr::write_volatile(address as *mut u8, value) },
+        // SAFETY: as above, for an I/O port reset register.
+        1 => unsafe { Port::<u8>::new(address as u16).write(value) },
+        _ => {}
+    }
+
+    // SAFETY: writing 0x02 then 0x06 to the reset control register asks
+    // the chipset for a hard reset; the port is unused elsewhere.
+    unsafe {
+        let mut port = Port::<u8>::new(RESET_CONTROL);
+        port.write(0x02);
+        port.write(0x06);
+    }
+
+    if HAS_8042.load(Ordering::Relaxed) {
+        // SAFETY: writing 0xFE to the keyboard controller command port
+        // pulses the CPU reset line.
+        unsafe { Port::<u8>::new(0x64).write(0xFE) };
+    }
+
+    // Last resort: an empty IDT turns the next exception into a triple
+    // fault, which resets the CPU.
+    let empty = x86_64::structures::DescriptorTablePointer {
+        limit: 0,
+        base: x86_64::VirtAddr::zero(),
+    };
+    // SAFETY: nothing runs after this; interrupts are disabled first so the
+    // empty IDT is only consulted for the int3 below.
+    unsafe {
+        x86_64::instructions::interrupts::disable();
+        x86_64::instructions::tables::lidt(&empty);
+        core::arch::asm!("int3", options(nomem, nostack));
+    }
+}
+
+/// Make a PSCI call that does not return on success.
+#[cfg(target_arch = "aarch64")]
+fn psci_call(function: u32) {
+    // SAFETY: PSCI SYSTEM_OFF and SYSTEM_RESET take no arguments and only
+    // return on failure; x0-x3 may be clobbered per the SMC calling
+    // convention.
+    unsafe {
+        if PSCI_SMC.load(Ordering::Relaxed) {
+            core::arch::asm!(
+                "smc #0",
+                inout("x0") function as u64 => _,
+                lateout("x1") _, lateout("x2") _, lateout("x3") _,
+                options(nomem, nostack),
+            );
+        } else {
+            core::arch::asm!(
+                "hvc #0",
+                inout("x0") function as u64 => _,
+                lateout("x1") _, lateout("x2") _, lateout("x3") _,
+                options(nomem, nostack),
+            );
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Thermal zones and trip point handling
+//!
+//! A thermal zone is a temperature sensor with trip points. The timer tick
+//! polls all zones once per [`POLL_INTERVAL_MS`] through [`poll`]:
+//! - at or above a zone's passive trip, the highest P-state the cpufreq
+//!   governor may pick drops one step per poll; once every zone is
+//!   [`PASSIVE_HYSTERESIS_MC`] below its passive trip, the cap is raised again
+//!   one step per poll
+//! - at a critical trip, init is asked to power the machine off
+//!
+//! Temperatures are in millidegrees Celsius, as in Linux sysfs.
+//!
+//! Built-in zones: the digital thermal sensor of Intel x86_64 CPUs. ACPI
+//! thermal zones (`_TMP`, `_PSV`, `_CRT`) need an AML interpreter and will
+//! [`register`] like any other zone once there is one.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use spin::RwLock;
+
+use super::system::{PowerAction, RequestReason};
+
+/// Time between two polls of the thermal zones.
+pub(crate) const POLL_INTERVAL_MS: u64 = 1_000;
+
+/// How far below its passive trip a zone must cool before the P-state cap
+/// is raised again.
+pub(crate) const PASSIVE_HYSTERESIS_MC: i32 = 5_000;
+
+/// Trip points of a thermal zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TripPoints {
+    /// Throttle the CPU at or above this temperature.
+    pub passive_mc: Option<i32>,
+    /// Power off at or above this temperature.
+    pub critical_mc: Option<i32>,
+}
+
+/// A temperature sensor with trip points.
+pub trait ThermalZone: Send + Sync {
+    /// Zone type as shown in sysfs (e.g. `x86_pkg_temp`, `acpitz`).
+    fn zone_type(&self) -> &'static str;
+
+    /// Current temperature, or `None` without a valid reading.
+    ///
+    /// Called from the timer tick: must not block.
+    fn temperature_mc(&self) -> Option<i32>;
+
+    /// The zone's trip points.
+    fn trips(&self) -> TripPoints;
+}
+
+/// A zone's readings at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneStatus {
+    /// Zone type, see [`ThermalZone::zone_type`].
+    pub zone_type: &'static str,
+    /// Current temperature, `None` without a valid reading.
+    pub temperature_mc: Option<i32>,
+    /// The zone's trip points.
+    pub trips: TripPoints,
+}
+
+static ZONES: RwLock<Vec<Box<dyn ThermalZone>>> = RwLock::new(Vec::new());
+
+/// Timestamp of the last poll.
+static LAST_POLL_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Set once a critical trip has been reported, so init is asked only once.
+static CRITICAL_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Direction the P-state cap should move in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Trend {
+    /// Every zone is well below its passive trip: raise the cap.
+    Cool,
+    /// Within the hysteresis band: leave the cap alone.
+    Hold,
+    /// A zone is at or above its passive trip: lower the cap.
+    Hot,
+}
+
+/// Where `temp_mc` stands relative to a passive trip.
+fn passive_trend(temp_mc: i32, passive_mc: i32) -> Trend {
+    if temp_mc >= passive_mc {
+        Trend::Hot
+    } else if temp_mc > passive_mc - PASSIVE_HYSTERESIS_MC {
+        Trend::Hold
+    } else {
+        Trend::Cool
+    }
+}
+
+/// Next P-state cap (`usize::MAX`: none) given `num_pstates` and a trend.
+fn step_cap(cap: usize, num_pstates: usize, trend: Trend) -> usize {
+    if num_pstates == 0 {
+        return usize::MAX;
+    }
+    let max_idx = num_pstates - 1;
+    let current = cap.min(max_idx);
+    match trend {
+        Trend::Hot => current.saturating_sub(1),
+        Trend::Hold => cap,
+        Trend::Cool if current + 1 >= max_idx => usize::MAX,
+        Trend::Cool => current + 1,
+    }
+}
+
+/// Add a thermal zone.
+pub fn register(zone: Box<dyn ThermalZone>) {
+    crate::println!("[THERMAL] Registered zone {}", zone.zone_type());
+    ZONES.write().push(zone);
+}
+
+/// Register the built-in zones. Called from [`super::init`].
+pub(crate) fn init() {
+    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
+    if let Some(sensor) = x86_dts::CoreSensor::probe() {
+        register(Box::new(sensor));
+    }
+}
+
+/// Readings of all zones, in registration order.
+pub fn zones() -> Vec<ZoneStatus> {
+    ZONES
+        .read()
+        .iter()
+        .map(|zone| ZoneStatus {
+            zone_type: zone.zone_type(),
+            temperature_mc: zone.temperature_mc(),
+            trips: zone.trips(),
+        })
+        .collect()
+}
+
+/// Check every zone against its trip points.
+///
+/// Called on every timer tick; acts at most once per [`POLL_INTERVAL_MS`]
+/// and skips the poll rather than wait for a lock.
+pub(crate) fn poll() {
+    let now = crate::arch::timer::get_timestamp_ms();
+    let last = LAST_POLL_MS.load(Ordering::Relaxed);
+    if last != 0 && now.saturating_sub(last) < POLL_INTERVAL_MS {
+        return;
+    }
+    LAST_POLL_MS.store(now.max(1), Ordering::Relaxed);
+
+    let Some(zones) = ZONES.try_read() else {
+        return;
+    };
+    if zones.is_empty() {
+        return;
+    }
+    let mut trend = Trend::Cool;
+    for zone in zones.iter() {
+        let Some(temp) = zone.temperature_mc() else {
+            continue;
+        };
+        let trips = zone.trips();
+        if let Some(critical) = trips.critical_mc {
+            if temp >= critical && !CRITICAL_REPORTED.swap(true, Ordering::AcqRel) {
+                super::system::request(PowerAction::PowerOff, RequestReason::CriticalTemperature);
+            }
+        }
+        if let Some(passive) = trips.passive_mc {
+            trend = trend.max(passive_trend(temp, passive));
+        }
+    }
+    drop(zones);
+
+    if let Some(num_pstates) = super::try_pstate_count() {
+        let cap = super::thermal_cap();
+        let next = step_cap(cap, num_pstates, trend);
+        if next != cap {
+            super::set_thermal_cap(next);
+        }
+    }
+}
+
+/// Digital thermal sensor of Intel CPUs (IA32_THERM_STATUS).
+#[cfg(all(target_arch = "x86_64", target_os = "none"))]
+mod x86_dts {
+    use core::arch::x86_64::__cpuid;
+
+    use super::{ThermalZone, TripPoints};
+    use crate::arch::x86_64::msr;
+
+    /// IA32_THERM_STATUS: bits 22:16 = degrees below TjMax, bit 31 = valid.
+    const IA32_THERM_STATUS: u32 = 0x19C;
+    /// MSR_TEMPERATURE_TARGET: bits 23:16 = TjMax in degrees Celsius.
+    const MSR_TEMPERATURE_TARGET: u32 = 0x1A2;
+    /// TjMax to assume when the CPU does not report one.
+    const DEFAULT_TJMAX_C: u32 = 100;
+    /// Passive trip below TjMax, where the CPU starts throttling itself.
+    const PASSIVE_BELOW_TJMAX_MC: i32 = 10_000;
+
+    /// Temperature of the core running the poll.
+    pub(super) struct CoreSensor {
+        tjmax_mc: i32,
+    }
+
+    impl CoreSensor {
+        /// Detect the sensor: Intel, with CPUID.06H:EAX[0] (DTS) set.
+        pub(super) fn probe() -> Option<Self> {
+            // SAFETY: CPUID is unprivileged; leaves 0, 1 and 6 exist on all
+            // x86_64 CPUs with a digital thermal sensor (checked below).
+            let (vendor, signature, power) = unsafe { (__cpuid(0), __cpuid(1), __cpuid(6)) };
+            let intel = vendor.ebx == u32::from_le_bytes(*b"Genu")
+                && vendor.edx == u32::from_le_bytes(*b"ineI")
+                && vendor.ecx == u32::from_le_bytes(*b"ntel");
+            if !intel || vendor.eax < 6 || power.eax & 1 == 0 {
+                return None;
+            }
+
+            // MSR_TEMPERATURE_TARGET exists from Nehalem (family 6, model
+            // 0x1A) on.
+            let family = (signature.eax >> 8) & 0xF;
+            let model = ((signature.eax >> 4) & 0xF) | (((signature.eax >> 16) & 0xF) << 4);
+            let mut tjmax = DEFAULT_TJMAX_C;
+            if family == 6 && model >= 0x1A {
+                let reported = ((msr::rdmsr(MSR_TEMPERATURE_TARGET) >> 16) & 0xFF) as u32;
+                if reported != 0 {
+                    tjmax = reported;
+                }
+            }
+            crate::println!("[THERMAL] CPU digital thermal sensor, TjMax {} C", tjmax);
+            Some(Self {
+                tjmax_mc: tjmax as i32 * 1000,
+            })
+        }
+    }
+
+    impl ThermalZone for CoreSensor {
+        fn zone_type(&self) -> &'static str {
+            "x86_pkg_temp"
+        }
+
+        fn temperature_mc(&self) -> Option<i32> {
+            let status = msr::rdmsr(IA32_THERM_STATUS);
+            if status & (1 << 31) == 0 {
+                return None;
+            }
+            let below = ((status >> 16) & 0x7F) as i32;
+            Some(self.tjmax_mc - below * 1000)
+        }
+
+        fn trips(&self) -> TripPoints {
+            // The readout saturates at TjMax, so that is the critical trip:
+            // the CPU is already throttling as hard as it can.
+            TripPoints {
+                passive_mc: Some(self.tjmax_mc - PASSIVE_BELOW_TJMAX_MC),
+                critical_mc: Some(self.tjmax_mc),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passive_trend() {
+        assert_eq!(passive_trend(90_000, 90_000), Trend::Hot);
+        assert_eq!(passive_trend(95_000, 90_000), Trend::Hot);
+        assert_eq!(passive_trend(89_999, 90_000), Trend::Hold);
+        assert_eq!(passive_trend(85_001, 90_000), Trend::Hold);
+        assert_eq!(passive_trend(85_000, 90_000), Trend::Cool);
+    }
+
+    #[test]
+    fn test_step_cap_lowers_while_hot() {
+        assert_eq!(step_cap(usize::MAX, 4, Trend::Hot), 2);
+        assert_eq!(step_cap(2, 4, Trend::Hot), 1);
+        assert_eq!(step_cap(0, 4, Trend::Hot), 0);
+    }
+
+    #[test]
+    fn test_step_cap_raises_when_cool() {
+        assert_eq!(step_cap(0, 4, Trend::Cool), 1);
+        assert_eq!(step_cap(1, 4, Trend::Cool), 2);
+        assert_eq!(step_cap(2, 4, Trend::Cool), usize::MAX);
+        assert_eq!(step_cap(usize::MAX, 4, Trend::Cool), usize::MAX);
+    }
+
+    #[test]
+    fn test_step_cap_holds() {
+        assert_eq!(step_cap(1, 4, Trend::Hold), 1);
+        assert_eq!(step_cap(usize::MAX, 4, Trend::Hold), usize::MAX);
+    }
+
+    #[test]
+    fn test_step_cap_without_pstates() {
+        assert_eq!(step_cap(1, 0, Trend::Hot), usize::MAX);
+    }
+}
@@ -14,11 +14,16 @@ use super::{
     lifecycle::create_scheduler_task,
     pcb::{Process, ProcessBuilder, ProcessState},
     table,
-    thread::ThreadBuilder,
+    thread::{Stack, ThreadBuilder},
     ProcessId, ProcessPriority,
 };
 #[allow(unused_imports)]
-use crate::{arch::context::ThreadContext, error::KernelError};
+use crate::{
+    arch::context::ThreadContext,
+    elf::dynamic::{AuxType, AuxVecEntry},
+    error::KernelError,
+    security::memory_protection::UserLayout,
+};
 
 /// Default stack sizes
 pub const DEFAULT_USER_STACK_SIZE: usize = 256 * 1024; // 256KB initial (grows via page faults)
@@ -72,7 +77,8 @@ pub fn create_process_with_options(
 ) -> Result<ProcessId, KernelError> {
     // Enforce process count limit before allocating resources.
     let current_count = table::PROCESS_TABLE.count();
-    if current_count >= super::MAX_PROCESSES {
+    let max_processes = crate::limits::max_processes();
+    if current_count >= max_processes {
         return Err(KernelError::ResourceExhausted {
             resource: "process table",
         });
@@ -95,7 +101,7 @@ pub fn create_process_with_options(
     }
 
     // Create the main thread
-    let main_thread =
+    let mut main_thread =
         ThreadBuilder::new(pid, format!("{}-main", options.name), options.entry_point)
             .user_stack_size(options.user_stack_size)
             .kernel_stack_size(options.kernel_stack_size)
@@ -108,25 +114,9 @@ pub fn create_process_with_options(
     // but does not map them. We call vas.map_page() for each page, which
     // allocates new physical frames and creates the PTE entries.
     {
-        let user_base = main_thread.user_stack.base;
-        let user_size = main_thread.user_stack.size;
-        let num_pages = user_size / 4096;
-
+        let layout = new_user_layout();
         let mut memory_space = process.memory_space.lock();
-
-        let stack_flags = crate::mm::PageFlags::PRESENT
-            | crate::mm::PageFlags::USER
-            | crate::mm::PageFlags::WRITABLE
-            | crate::mm::PageFlags::NO_EXECUTE;
-        for i in 0..num_pages {
-            let vaddr = user_base + i * 4096;
-            memory_space.map_page(vaddr, stack_flags)?;
-        }
-
-        // Update VAS stack_top to match the main thread's actual allocated stack
-        // This ensures setup_exec_stack() uses the correct stack range
-        memory_space.set_stack_top(user_base + user_size);
-        memory_space.set_stack_size(user_size);
+        apply_user_layout(&mut memory_space, &layout, &mut main_thread.user_stack)?;
     }
 
     // Add thread to process
@@ -138,7 +128,13 @@ pub fn create_process_with_options(
     let envp_refs: Vec<&str> = options.envp.iter().map(|s| s.as_str()).collect();
 
     // Get the process before adding to table so we can set up the stack
-    let stack_top = setup_exec_stack(&process, &argv_refs, &envp_refs, None)?;
+    let aux_vector = [
+        AuxVecEntry::new(AuxType::AtPagesz, crate::mm::PAGE_SIZE as u64),
+        AuxVecEntry::new(AuxType::AtEntry, options.entry_point as u64),
+    ];
+    let stack_top = setup_exec_stack(&process, &argv_refs, &envp_refs, &options.name, &aux_vector)?;
+
+    set_env_vars(&process, &envp_refs);
 
     // Update the thread context with the adjusted stack pointer
     if let Some(thread) = process.get_thread(tid) {
@@ -235,6 +231,22 @@ pub fn parse_shebang(data: &[u8]) -> Option<(String, Option<String>)> {
     }
 }
 
+/// Populate the process's `env_vars` from `envp` (`NAME=VALUE` strings).
+///
+/// This makes environment variables available to kernel-side lookups
+/// (e.g. PATH resolution in search_path()) without reading user memory.
+/// Fork copies the map to the child; exec replaces it.
+#[cfg(feature = "alloc")]
+fn set_env_vars(process: &Process, envp: &[&str]) {
+    let mut env_map = process.env_vars.lock();
+    env_map.clear();
+    for &env_str in envp {
+        if let Some((key, value)) = env_str.split_once('=') {
+            env_map.insert(String::from(key), String::from(value));
+        }
+    }
+}
+
 /// Search for an executable by name in PATH directories
 ///
 /// If `name` contains a `/`, it is treated as an explicit path and returned
@@ -297,6 +309,18 @@ pub fn search_path(name: &str) -> Option<String> {
 /// if the path does not start with `/`, standard directories are searched.
 #[cfg(feature = "alloc")]
 pub fn exec_process(path: &str, argv: &[&str], envp: &[&str]) -> Result<(), KernelError> {
+    exec_with_profile(path, argv, envp, None)
+}
+
+/// [`exec_process`], confining the new image by `profile` (the profile of
+/// the script an interpreter runs) or else by its own sandbox profile.
+#[cfg(feature = "alloc")]
+fn exec_with_profile(
+    path: &str,
+    argv: &[&str],
+    envp: &[&str],
+    profile: Option<alloc::sync::Arc<crate::security::sandbox::Profile>>,
+) -> Result<(), KernelError> {
     use crate::{elf::ElfLoader, fs};
 
     let process = super::current_process().ok_or(KernelError::ProcessNotFound { pid: 0 })?;
@@ -309,10 +333,25 @@ pub fn exec_process(path: &str, argv: &[&str], envp: &[&str]) -> Result<(), Kern
         String::from(path)
     };
 
+    // A sandboxed caller may only run what its profile lets it read
+    crate::security::sandbox::check_path(
+        process,
+        &resolved_path,
+        crate::security::AccessType::Execute,
+        true,
+    )?;
+
     // Step 1: Load new program from filesystem
     let file_data = fs::read_file(&resolved_path)
         .map_err(|_| KernelError::FsError(crate::error::FsError::NotFound))?;
 
+    // Step 1a: Find the sandbox profile to apply once past the point of no
+    // return. A broken profile naming the program refuses the exec here.
+    let profile = match profile {
+        Some(profile) => Some(profile),
+        None => crate::security::sandbox::profile_for(&resolved_path)?,
+    };
+
     // Step 1b: Check for shebang (#!) and delegate to interpreter if found
     if let Some((interpreter, opt_arg)) = parse_shebang(&file_data) {
         // Build new argv: [interpreter, opt_arg?, script_path, original_argv[1..]]
@@ -335,10 +374,21 @@ pub fn exec_process(path: &str, argv: &[&str], envp: &[&str]) -> Result<(), Kern
             new_argv.extend_from_slice(&argv[1..]);
         }
 
-        // Recursively exec the interpreter
-        return exec_process(&interpreter, &new_argv, envp);
+        // Recursively exec the interpreter, confined as the script would be
+        return exec_with_profile(&interpreter, &new_argv, envp, profile);
     }
 
+    // Step 1c: Pick the new image's layout. Position-independent executables
+    // are slid to the (randomized) PIE base; fixed-address ones load as linked.
+    let binary = ElfLoader::new()
+        .parse(&file_data)
+        .map_err(|_| KernelError::InvalidArgument {
+            name: "elf",
+            value: "failed to parse ELF binary",
+        })?;
+    let layout = new_user_layout();
+    let pie_bias = ElfLoader::pie_bias(&binary, layout.pie_base as u64);
+
     // Step 2: Clear current address space and load new program
     let entry_point = {
         let mut memory_space = process.memory_space.lock();
@@ -351,50 +401,48 @@ pub fn exec_process(path: &str, argv: &[&str], envp: &[&str]) -> Result<(), Kern
 
         // Re-map the main thread's user stack into the fresh VAS. `clear()`
         // removed all user mappings; without this, the new image would return
-        // to an unmapped stack (the /bin/sh crash).
+        // to an unmapped stack (the /bin/sh crash). Each exec gets a new
+        // stack position and mmap base.
         if let Some(main_tid) = process.get_main_thread_id() {
-            if let Some(main_thread) = process.get_thread(main_tid) {
-                let user_base = main_thread.user_stack.base;
-                let user_size = main_thread.user_stack.size;
-                let flags = crate::mm::PageFlags::PRESENT
-                    | crate::mm::PageFlags::USER
-                    | crate::mm::PageFlags::WRITABLE
-                    | crate::mm::PageFlags::NO_EXECUTE;
-                let pages = user_size / 4096;
-                for i in 0..pages {
-                    let vaddr = user_base + i * 4096;
-                    memory_space.map_page(vaddr, flags)?;
-                }
-                memory_space.set_stack_top(user_base + user_size);
-                memory_space.set_stack_size(user_size);
+            if let Some(main_thread) = process.threads.lock().get_mut(&main_tid) {
+                apply_user_layout(&mut memory_space, &layout, &mut main_thread.user_stack)?;
             }
         }
 
         // Load ELF segments into address space and get entry point
-        ElfLoader::load(&file_data, &mut memory_space)?
+        let entry = ElfLoader::load_at(&file_data, &mut memory_space, pie_bias)?;
+        memory_space.set_backing(binary.load_base + pie_bias, binary.load_size, path);
+        entry
     };
 
+    // JIT-capable binaries (PT_WXNEEDED) may create W+X mappings
+    process
+        .wx_exempt
+        .store(binary.wx_needed, core::sync::atomic::Ordering::Release);
+    *process.symbols.lock() =
+        crate::elf::symbols::SymbolMap::from_elf(&file_data, pie_bias).map(alloc::sync::Arc::new);
+    crate::security::sandbox::install(process, profile);
+    process.syscall_filters.lock().exec();
+    // The old image's allocator statistics are gone with its memory
+    process
+        .heap_stats_addr
+        .store(0, core::sync::atomic::Ordering::Release);
+
     // Step 2b: Check for dynamic linking
     let (final_entry, aux_vector) = {
-        let loader = ElfLoader::new();
-        let elf_binary = loader
-            .parse(&file_data)
-            .map_err(|_| KernelError::InvalidArgument {
-                name: "elf",
-                value: "failed to parse ELF for dynamic linking check",
-            })?;
+        let phdr_addr = crate::elf::dynamic::phdr_vaddr(&file_data, &binary) + pie_bias;
+        let mut elf_binary = binary;
+        elf_binary.entry_point += pie_bias;
+        elf_binary.load_base += pie_bias;
 
         if elf_binary.dynamic && elf_binary.interpreter.is_some() {
             // Dynamically linked -- load interpreter and build aux vector
-            let dyn_info = crate::elf::dynamic::prepare_dynamic_linking(
-                &file_data,
-                &elf_binary,
-                elf_binary.load_base,
-            )?
-            .ok_or(KernelError::InvalidArgument {
-                name: "dynamic",
-                value: "binary has interpreter but prepare_dynamic_linking returned None",
-            })?;
+            let dyn_info =
+                crate::elf::dynamic::prepare_dynamic_linking(&file_data, &elf_binary, phdr_addr)?
+                    .ok_or(KernelError::InvalidArgument {
+                    name: "dynamic",
+                    value: "binary has interpreter but prepare_dynamic_linking returned None",
+                })?;
 
             // Load interpreter LOAD segments into the process address space.
             // The interpreter is a separate ELF loaded at its own base address
@@ -404,13 +452,21 @@ pub fn exec_process(path: &str, argv: &[&str], envp: &[&str]) -> Result<(), Kern
             {
                 let mut memory_space = process.memory_space.lock();
                 let _interp_entry = ElfLoader::load(&interp_data, &mut memory_space)?;
+                if let Ok(interp) = ElfLoader::new().parse(&interp_data) {
+                    memory_space.set_backing(
+                        interp.load_base,
+                        interp.load_size,
+                        &dyn_info.interp_path,
+                    );
+                }
             }
 
             // Entry point is the interpreter, not the main binary
-            (dyn_info.interp_entry, Some(dyn_info.aux_vector))
+            (dyn_info.interp_entry, dyn_info.aux_vector)
         } else {
-            // Statically linked -- use binary entry directly, no aux vector
-            (entry_point, None)
+            // Statically linked -- libc still reads the aux vector
+            let aux_vector = crate::elf::dynamic::build_aux_vector(&elf_binary, phdr_addr, 0, 0, 0);
+            (entry_point, aux_vector)
         }
     };
 
@@ -490,22 +546,11 @@ pub fn exec_process(path: &str, argv: &[&str], envp: &[&str]) -> Result<(), Kern
     }
 
     // Step 3: Setup new stack with arguments, environment, and aux vector
-    let stack_top = setup_exec_stack(process, argv, envp, aux_vector.as_deref())?;
+    let stack_top = setup_exec_stack(process, argv, envp, &resolved_path, &aux_vector)?;
+    super::vdso::sync_thread_id(process);
 
-    // Step 3b: Populate the process's env_vars BTreeMap from envp.
-    // This makes environment variables available to kernel-side lookups
-    // (e.g. PATH resolution in search_path()) without reading user memory.
-    {
-        let mut env_map = process.env_vars.lock();
-        env_map.clear();
-        for &env_str in envp {
-            if let Some(eq_pos) = env_str.find('=') {
-                let key = String::from(&env_str[..eq_pos]);
-                let value = String::from(&env_str[eq_pos + 1..]);
-                env_map.insert(key, value);
-            }
-        }
-    }
+    // Step 3b: Replace the process's kernel-side copy of the environment
+    set_env_vars(process, envp);
 
     // Step 4: Reset thread context to new entry point
     {
@@ -558,6 +603,53 @@ pub fn exec_process(_path: &str, _argv: &[&str], _envp: &[&str]) -> Result<(), K
     })
 }
 
+/// Layout for a new user image.
+///
+/// Randomizing it draws from the RNG, which is only usable this early on
+/// x86_64 (see the memory hardening in `create_process_with_options`);
+/// other architectures get the fixed layout.
+#[cfg(feature = "alloc")]
+fn new_user_layout() -> UserLayout {
+    #[cfg(target_arch = "x86_64")]
+    {
+        crate::security::memory_protection::user_layout()
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        UserLayout::fixed()
+    }
+}
+
+/// Map the main-thread stack and the vDSO of a fresh address space and set
+/// its mmap base according to `layout`. `stack` is moved to where it was
+/// mapped, so that exit unmaps and the scheduler enters the right range.
+#[cfg(feature = "alloc")]
+fn apply_user_layout(
+    memory_space: &mut crate::mm::VirtualAddressSpace,
+    layout: &UserLayout,
+    stack: &mut Stack,
+) -> Result<(), KernelError> {
+    let stack_flags = crate::mm::PageFlags::PRESENT
+        | crate::mm::PageFlags::USER
+        | crate::mm::PageFlags::WRITABLE
+        | crate::mm::PageFlags::NO_EXECUTE;
+    let stack_size = stack.size;
+    let stack_base = layout.stack_top - stack_size;
+    for i in 0..stack_size / 4096 {
+        memory_space.map_page(stack_base + i * 4096, stack_flags)?;
+    }
+    stack.base = stack_base;
+    stack
+        .sp
+        .store(layout.stack_top, core::sync::atomic::Ordering::Release);
+
+    // setup_exec_stack() and stack growth work from these
+    memory_space.set_stack_top(layout.stack_top);
+    memory_space.set_stack_size(stack_size);
+    memory_space.set_mmap_base(layout.mmap_base);
+    super::vdso::install(memory_space)
+}
+
 /// Write a value to a user-space stack address via the physical memory window.
 ///
 /// The process's page tables map `vaddr` to a physical frame. We look up the
@@ -634,18 +726,22 @@ unsafe fn write_bytes_to_user_stack(
     }
 }
 
-/// Setup stack for exec with arguments, environment, and optional auxiliary
-/// vector.
+/// Setup stack for exec with arguments, environment, and auxiliary vector.
 ///
 /// Writes the full argc/argv/envp/auxv layout to the user stack via the
-/// physical memory window. The layout (growing downward from stack_top) is:
+/// physical memory window. `aux_vector` carries the loader's entries;
+/// AT_RANDOM, AT_EXECFN and AT_VDSO are appended here because they point
+/// into the stack or the vDSO. The layout (growing downward from stack_top)
+/// is:
 ///
 /// ```text
 /// [high addresses]
+///   16 random bytes          <- AT_RANDOM
+///   execfn (null-terminated) <- AT_EXECFN
 ///   envp strings (null-terminated)
 ///   argv strings (null-terminated)
 ///   padding (16-byte alignment)
-///   AT_NULL (0, 0)           <- auxv terminator (if present)
+///   AT_NULL (0, 0)           <- auxv terminator
 ///   auxv[N-1] (type, value)
 ///   ...
 ///   auxv[0] (type, value)
@@ -665,7 +761,8 @@ fn setup_exec_stack(
     process: &Process,
     argv: &[&str],
     envp: &[&str],
-    aux_vector: Option<&[crate::elf::dynamic::AuxVecEntry]>,
+    execfn: &str,
+    aux_vector: &[AuxVecEntry],
 ) -> Result<usize, KernelError> {
     let memory_space = process.memory_space.lock();
 
@@ -677,6 +774,42 @@ fn setup_exec_stack(
     // ---- Phase 1: Write strings from the top of the stack downward ----
     let mut string_sp = stack_top;
 
+    // AT_RANDOM bytes seed the stack protector and libc's hash keys
+    let mut random = [0u8; 16];
+    crate::crypto::random::get_random()
+        .fill_bytes(&mut random)
+        .map_err(|_| KernelError::InvalidState {
+            expected: "random bytes for AT_RANDOM",
+            actual: "RNG failure",
+        })?;
+    string_sp -= random.len();
+    let random_addr = string_sp;
+    // SAFETY: string_sp is within the stack mapping.
+    unsafe {
+        write_bytes_to_user_stack(&memory_space, random_addr, &random);
+    }
+
+    string_sp -= execfn.len() + 1;
+    let execfn_addr = string_sp;
+    // SAFETY: string_sp is within the stack mapping.
+    unsafe {
+        write_bytes_to_user_stack(&memory_space, execfn_addr, execfn.as_bytes());
+        write_bytes_to_user_stack(&memory_space, execfn_addr + execfn.len(), &[0]);
+    }
+
+    let mut auxv: Vec<AuxVecEntry> = aux_vector
+        .iter()
+        .copied()
+        .filter(|entry| entry.type_id != AuxType::AtNull)
+        .collect();
+    auxv.push(AuxVecEntry::new(AuxType::AtRandom, random_addr as u64));
+    auxv.push(AuxVecEntry::new(AuxType::AtExecfn, execfn_addr as u64));
+    auxv.push(AuxVecEntry::new(
+        AuxType::AtVdso,
+        super::vdso::VDSO_BASE as u64,
+    ));
+    auxv.push(AuxVecEntry::new(AuxType::AtNull, 0));
+
     // Write envp strings and record their user-space addresses
     let mut envp_addrs: Vec<usize> = Vec::with_capacity(envp.len());
     for &env in envp.iter().rev() {
@@ -712,7 +845,7 @@ fn setup_exec_stack(
 
     // Ensure space for: argc + argv ptrs + NULL + envp ptrs + NULL + auxv entries
     // Each auxv entry is 2 usizes (type, value)
-    let auxv_slots = aux_vector.map(|v| v.len() * 2).unwrap_or(0);
+    let auxv_slots = auxv.len() * 2;
     let ptrs_needed = 1 + argv.len() + 1 + envp.len() + 1 + auxv_slots;
     sp -= ptrs_needed * core::mem::size_of::<usize>();
     // Re-align to 16 bytes (ABI requirement)
@@ -771,22 +904,20 @@ fn setup_exec_stack(
     }
     write_pos += core::mem::size_of::<usize>();
 
-    // Write auxiliary vector (if present, for dynamically linked binaries)
-    if let Some(auxv) = aux_vector {
-        for entry in auxv {
-            // Each aux entry is two usize values: type, value
-            // SAFETY: write_pos is within the stack region, reserved in
-            // ptrs_needed calculation above.
-            unsafe {
-                write_to_user_stack(&memory_space, write_pos, entry.type_id as usize);
-            }
-            write_pos += core::mem::size_of::<usize>();
-            // SAFETY: write_pos is within the stack region.
-            unsafe {
-                write_to_user_stack(&memory_space, write_pos, entry.value as usize);
-            }
-            write_pos += core::mem::size_of::<usize>();
+    // Write auxiliary vector
+    for entry in &auxv {
+        // Each aux entry is two usize values: type, value
+        // SAFETY: write_pos is within the stack region, reserved in
+        // ptrs_needed calculation above.
+        unsafe {
+            write_to_user_stack(&memory_space, write_pos, entry.type_id as usize);
         }
+        write_pos += core::mem::size_of::<usize>();
+        // SAFETY: write_pos is within the stack region.
+        unsafe {
+            write_to_user_stack(&memory_space, write_pos, entry.value as usize);
+        }
+        write_pos += core::mem::size_of::<usize>();
     }
 
     Ok(sp)
@@ -0,0 +1,336 @@
+//! Process credentials
+//!
+//! Each process carries POSIX-style credentials: real, effective and saved
+//! user/group IDs plus a supplementary group list. The effective IDs are used
+//! for permission checks; the real and saved IDs let a set-ID program drop
+//! and regain privilege.
+//!
+//! The privilege rules follow POSIX: a process with effective UID 0 may set
+//! any ID, while an unprivileged process may only move between its current
+//! real, effective and saved IDs and may not change its supplementary groups.
+
+use crate::fs::Permissions;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Maximum number of supplementary groups per process.
+pub const NGROUPS_MAX: usize = 32;
+
+/// Sentinel meaning "leave this ID unchanged" (`-1` from userland).
+pub const ID_UNCHANGED: u32 = u32::MAX;
+
+/// Superuser UID.
+pub const ROOT_UID: u32 = 0;
+
+/// Read permission (`R_OK`).
+pub const ACCESS_READ: u32 = 4;
+/// Write permission (`W_OK`).
+pub const ACCESS_WRITE: u32 = 2;
+/// Execute / search permission (`X_OK`).
+pub const ACCESS_EXEC: u32 = 1;
+
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+/// Credential change errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredError {
+    /// The caller lacks the privilege for the requested change (EPERM).
+    NotPermitted,
+    /// An ID is out of range, e.g. `ID_UNCHANGED` where it is not allowed.
+    InvalidId,
+    /// More than `NGROUPS_MAX` supplementary groups.
+    TooManyGroups,
+}
+
+// ---------------------------------------------------------------------------
+// Credentials
+// ---------------------------------------------------------------------------
+
+/// User and group identity of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials {
+    pub ruid: u32,
+    pub euid: u32,
+    pub suid: u32,
+    pub rgid: u32,
+    pub egid: u32,
+    pub sgid: u32,
+    groups: [u32; NGROUPS_MAX],
+    ngroups: usize,
+}
+
+impl Credentials {
+    /// Credentials with all IDs set to `uid`/`gid` and no supplementary
+    /// groups.
+    pub const fn new(uid: u32, gid: u32) -> Self {
+        Self {
+            ruid: uid,
+            euid: uid,
+            suid: uid,
+            rgid: gid,
+            egid: gid,
+            sgid: gid,
+            groups: [0; NGROUPS_MAX],
+            ngroups: 0,
+        }
+    }
+
+    /// Superuser credentials.
+    pub const fn root() -> Self {
+        Self::new(ROOT_UID, 0)
+    }
+
+    /// Whether the effective UID is the superuser.
+    pub fn is_privileged(&self) -> bool {
+        self.euid == ROOT_UID
+    }
+
+    /// Supplementary group list.
+    pub fn groups(&self) -> &[u32] {
+        &self.groups[..self.ngroups]
+    }
+
+    /// Whether `gid` is the effective GID or a supplementary group.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.egid == gid || self.groups().contains(&gid)
+    }
+
+    fn holds_uid(&self, uid: u32) -> bool {
+        uid == self.ruid || uid == self.euid || uid == self.suid
+    }
+
+    fn holds_gid(&self, gid: u32) -> bool {
+        gid == self.rgid || gid == self.egid || gid == self.sgid
+    }
+
+    /// `setuid()`: privileged callers set all three UIDs, others may only
+    /// set the effective UID to the real or saved UID.
+    pub fn set_uid(&mut self, uid: u32) -> Result<(), CredError> {
+        if uid == ID_UNCHANGED {
+            return Err(CredError::InvalidId);
+        }
+        if self.is_privileged() {
+            self.ruid = uid;
+            self.euid = uid;
+            self.suid = uid;
+        } else if uid == self.ruid || uid == self.suid {
+            self.euid = uid;
+        } else {
+            return Err(CredError::NotPermitted);
+        }
+        Ok(())
+    }
+
+    /// `setgid()`: same rules as [`set_uid`](Self::set_uid) for group IDs.
+    pub fn set_gid(&mut self, gid: u32) -> Result<(), CredError> {
+        if gid == ID_UNCHANGED {
+            return Err(CredError::InvalidId);
+        }
+        if self.is_privileged() {
+            self.rgid = gid;
+            self.egid = gid;
+            self.sgid = gid;
+        } else if gid == self.rgid || gid == self.sgid {
+            self.egid = gid;
+        } else {
+            return Err(CredError::NotPermitted);
+        }
+        Ok(())
+    }
+
+    /// `setreuid()`: unprivileged callers may set the real UID to the real
+    /// or effective UID and the effective UID to any of the three. The saved
+    /// UID follows the new effective UID whenever the real UID is set or the
+    /// effective UID moves away from the old real UID.
+    pub fn set_reuid(&mut self, ruid: u32, euid: u32) -> Result<(), CredError> {
+        if !self.is_privileged() {
+            if ruid != ID_UNCHANGED && ruid != self.ruid && ruid != self.euid {
+                return Err(CredError::NotPermitted);
+            }
+            if euid != ID_UNCHANGED && !self.holds_uid(euid) {
+                return Err(CredError::NotPermitted);
+            }
+        }
+        let old_ruid = self.ruid;
+        if ruid != ID_UNCHANGED {
+            self.ruid = ruid;
+        }
+        if euid != ID_UNCHANGED {
+            self.euid = euid;
+        }
+        if ruid != ID_UNCHANGED || (euid != ID_UNCHANGED && euid != old_ruid) {
+            self.suid = self.euid;
+        }
+        Ok(())
+    }
+
+    /// `setregid()`: same rules as [`set_reuid`](Self::set_reuid) for group
+    /// IDs.
+    pub fn set_regid(&mut self, rgid: u32, egid: u32) -> Result<(), CredError> {
+        if !self.is_privileged() {
+            if rgid != ID_UNCHANGED && rgid != self.rgid && rgid != self.egid {
+                return Err(CredError::NotPermitted);
+            }
+            if egid != ID_UNCHANGED && !self.holds_gid(egid) {
+                return Err(CredError::NotPermitted);
+            }
+        }
+        let old_rgid = self.rgid;
+        if rgid != ID_UNCHANGED {
+            self.rgid = rgid;
+        }
+        if egid != ID_UNCHANGED {
+            self.egid = egid;
+        }
+        if rgid != ID_UNCHANGED || (egid != ID_UNCHANGED && egid != old_rgid) {
+            self.sgid = self.egid;
+        }
+        Ok(())
+    }
+
+    /// `setgroups()`: replace the supplementary group list. Privileged only.
+    pub fn set_groups(&mut self, groups: &[u32]) -> Result<(), CredError> {
+        if !self.is_privileged() {
+            return Err(CredError::NotPermitted);
+        }
+        if groups.len() > NGROUPS_MAX {
+            return Err(CredError::TooManyGroups);
+        }
+        if groups.contains(&ID_UNCHANGED) {
+            return Err(CredError::InvalidId);
+        }
+        self.groups[..groups.len()].copy_from_slice(groups);
+        self.ngroups = groups.len();
+        Ok(())
+    }
+
+    /// Check `mask` (a combination of `ACCESS_*` bits) against a file owned
+    /// by `owner`/`group` with permission bits `perms`.
+    ///
+    /// The superuser may always read and write, and may execute if any
+    /// execute bit is set (or `is_dir`, where execute means search).
+    /// Otherwise exactly one class applies: owner, then group (effective or
+    /// supplementary), then other.
+    pub fn may_access(
+        &self,
+        owner: u32,
+        group: u32,
+        perms: &Permissions,
+        is_dir: bool,
+        mask: u32,
+    ) -> bool {
+        if self.is_privileged() {
+            let any_exec = perms.owner_exec || perms.group_exec || perms.other_exec;
+            return mask & ACCESS_EXEC == 0 || is_dir || any_exec;
+        }
+
+        let (r, w, x) = if self.euid == owner {
+            (perms.owner_read, perms.owner_write, perms.owner_exec)
+        } else if self.in_group(group) {
+            (perms.group_read, perms.group_write, perms.group_exec)
+        } else {
+            (perms.other_read, perms.other_write, perms.other_exec)
+        };
+
+        (mask & ACCESS_READ == 0 || r)
+            && (mask & ACCESS_WRITE == 0 || w)
+            && (mask & ACCESS_EXEC == 0 || x)
+    }
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Self::root()
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_setuid_sets_all() {
+        let mut c = Credentials::root();
+        c.set_uid(1000).unwrap();
+        assert_eq!((c.ruid, c.euid, c.suid), (1000, 1000, 1000));
+        // Privilege is gone for good
+        assert_eq!(c.set_uid(0), Err(CredError::NotPermitted));
+    }
+
+    #[test]
+    fn test_unprivileged_setuid_saved() {
+        // A set-uid-root binary that dropped to its real uid: real 1000,
+        // effective 1000, saved 0
+        let mut c = Credentials::new(1000, 100);
+        c.suid = 0;
+        assert_eq!(c.set_uid(2000), Err(CredError::NotPermitted));
+        // The saved uid may be regained, and as root it then sets all three
+        c.set_uid(0).unwrap();
+        assert_eq!((c.ruid, c.euid, c.suid), (1000, 0, 0));
+        c.set_uid(1000).unwrap();
+        assert_eq!((c.ruid, c.euid, c.suid), (1000, 1000, 1000));
+    }
+
+    #[test]
+    fn test_setreuid_rules() {
+        let mut c = Credentials::new(1000, 100);
+        c.euid = 0;
+        c.suid = 0;
+        // Temporarily drop to the real uid, saved stays 0
+        c.set_reuid(ID_UNCHANGED, 1000).unwrap();
+        assert_eq!((c.ruid, c.euid, c.suid), (1000, 1000, 0));
+        // Regain via the saved uid
+        c.set_reuid(ID_UNCHANGED, 0).unwrap();
+        assert_eq!(c.euid, 0);
+        // Permanently drop: setting ruid also updates suid
+        c.set_reuid(1000, 1000).unwrap();
+        assert_eq!((c.ruid, c.euid, c.suid), (1000, 1000, 1000));
+        assert_eq!(c.set_reuid(ID_UNCHANGED, 0), Err(CredError::NotPermitted));
+    }
+
+    #[test]
+    fn test_setgroups_privileged_only() {
+        let mut c = Credentials::root();
+        c.set_groups(&[10, 20]).unwrap();
+        assert_eq!(c.groups(), &[10, 20]);
+        assert!(c.in_group(20));
+        let mut user = Credentials::new(1000, 100);
+        assert_eq!(user.set_groups(&[0]), Err(CredError::NotPermitted));
+        let too_many = [1u32; NGROUPS_MAX + 1];
+        assert_eq!(c.set_groups(&too_many), Err(CredError::TooManyGroups));
+    }
+
+    #[test]
+    fn test_may_access_classes() {
+        // rw-r----- owned by 1000:100
+        let perms = Permissions::from_mode(0o640);
+        let owner = Credentials::new(1000, 1);
+        let member = {
+            let mut c = Credentials::root();
+            c.set_groups(&[100]).unwrap();
+            c.set_uid(2000).unwrap();
+            c
+        };
+        let other = Credentials::new(3000, 300);
+
+        assert!(owner.may_access(1000, 100, &perms, false, ACCESS_READ | ACCESS_WRITE));
+        assert!(member.may_access(1000, 100, &perms, false, ACCESS_READ));
+        assert!(!member.may_access(1000, 100, &perms, false, ACCESS_WRITE));
+        assert!(!other.may_access(1000, 100, &perms, false, ACCESS_READ));
+    }
+
+    #[test]
+    fn test_may_access_root() {
+        let root = Credentials::root();
+        let none = Permissions::from_mode(0o000);
+        assert!(root.may_access(1000, 100, &none, false, ACCESS_READ | ACCESS_WRITE));
+        assert!(!root.may_access(1000, 100, &none, false, ACCESS_EXEC));
+        assert!(root.may_access(1000, 100, &none, true, ACCESS_EXEC));
+        let exec = Permissions::from_mode(0o100);
+        assert!(root.may_access(1000, 100, &exec, false, ACCESS_EXEC));
+    }
+}
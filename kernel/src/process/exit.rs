@@ -148,6 +148,9 @@ pub fn wait_process_with_options(
                     // Remove from children list
                     current.children.lock().retain(|&p| p != *child_pid);
 
+                    // Charge the child's CPU time to our RUSAGE_CHILDREN
+                    current.add_child_times(child);
+
                     // Remove from process table
                     table::remove_process(*child_pid);
 
@@ -531,6 +534,11 @@ pub fn cleanup_process(process: &Process) {
         process.pid.0
     );
 
+    // If this is a device driver, stop its device first: DMA into the
+    // memory released below must not outlive the process.
+    #[cfg(feature = "alloc")]
+    crate::services::devmgr::driver_exited(process.pid);
+
     // Release memory (VAS-tracked data frames + page table subtrees)
     {
         let mut memory_space = process.memory_space.lock();
@@ -575,6 +583,9 @@ pub fn cleanup_process(process: &Process) {
         }
     }
 
+    // Disarm interval timers so no SIGALRM/SIGPROF is posted to a zombie
+    process.itimers.clear();
+
     // Release capabilities
     {
         let cap_space = process.capability_space.lock();
@@ -587,6 +598,8 @@ pub fn cleanup_process(process: &Process) {
     {
         use crate::ipc;
 
+        ipc::namespace::remove_process(process.pid);
+
         // Remove all endpoints owned by this process from the global registry
         match ipc::remove_process_endpoints(process.pid) {
             Ok(count) => {
@@ -609,6 +622,10 @@ pub fn cleanup_process(process: &Process) {
         process.ipc_endpoints.lock().clear();
     }
 
+    // Detach the process's packet rings from their interfaces
+    #[cfg(feature = "net")]
+    crate::net::packet_ring::release_process(process.pid);
+
     // Close all open file descriptors.
     // Log a warning if the process has more than 3 fds open (stdin/stdout/stderr).
     // This helps identify fd leaks during heavy workloads like BusyBox compilation
@@ -31,11 +31,11 @@ pub fn fork_process() -> Result<ProcessId, KernelError> {
     // This prevents unbounded process table growth during workloads
     // like BusyBox native compilation (213+ sequential fork+exec+wait).
     let current_count = table::PROCESS_TABLE.count();
-    if current_count >= super::MAX_PROCESSES {
+    let max_processes = crate::limits::max_processes();
+    if current_count >= max_processes {
         println!(
             "[PROCESS] fork: process limit reached ({}/{})",
-            current_count,
-            super::MAX_PROCESSES
+            current_count, max_processes
         );
         return Err(KernelError::ResourceExhausted {
             resource: "process table",
@@ -44,6 +44,7 @@ pub fn fork_process() -> Result<ProcessId, KernelError> {
 
     let current_process =
         super::current_process().ok_or(KernelError::ProcessNotFound { pid: 0 })?;
+    super::rlimit::check_nproc(current_process)?;
 
     let current_thread = super::current_thread().ok_or(KernelError::ThreadNotFound { tid: 0 })?;
 
@@ -64,6 +65,7 @@ pub fn fork_process() -> Result<ProcessId, KernelError> {
 
         // Clone page tables and mapping metadata
         new_space.clone_from(&current_space)?;
+        super::vdso::map_shared_pages(&mut new_space)?;
 
         // Mark user-space pages as COW (shared, read-only)
         let user_pages = collect_user_pages(&current_space);
@@ -130,13 +132,11 @@ pub fn fork_process() -> Result<ProcessId, KernelError> {
         }
     }
 
-    // Inherit uid, gid, pgid, sid from parent
+    // Inherit credentials, pgid, sid from parent
     // (ProcessBuilder doesn't copy these, so do it manually)
-    // uid/gid are non-atomic, but the new_process is not yet visible
-    // to other threads, so this is safe.
-    // SAFETY: new_process is not yet added to the process table, so no
-    // other thread can access it concurrently.
     {
+        *new_process.creds.lock() = current_process.credentials();
+
         // pgid and sid are inherited from parent per POSIX
         let parent_pgid = current_process
             .pgid
@@ -150,6 +150,22 @@ pub fn fork_process() -> Result<ProcessId, KernelError> {
         new_process
             .sid
             .store(parent_sid, core::sync::atomic::Ordering::Release);
+        new_process.wx_exempt.store(
+            current_process
+                .wx_exempt
+                .load(core::sync::atomic::Ordering::Acquire),
+            core::sync::atomic::Ordering::Release,
+        );
+        *new_process.symbols.lock() = current_process.symbols.lock().clone();
+        *new_process.sandbox.lock() = current_process.sandbox.lock().clone();
+        *new_process.syscall_filters.lock() = current_process.syscall_filters.lock().clone();
+        new_process.heap_stats_addr.store(
+            current_process
+                .heap_stats_addr
+                .load(core::sync::atomic::Ordering::Acquire),
+            core::sync::atomic::Ordering::Release,
+        );
+        new_process.rlimits.inherit(&current_process.rlimits);
     }
 
     // Create thread in new process matching current thread
@@ -273,11 +289,11 @@ pub fn fork_process() -> Result<ProcessId, KernelError> {
 #[cfg(feature = "alloc")]
 pub fn cow_fork() -> Result<ProcessId, KernelError> {
     let current_count = table::PROCESS_TABLE.count();
-    if current_count >= super::MAX_PROCESSES {
+    let max_processes = crate::limits::max_processes();
+    if current_count >= max_processes {
         println!(
             "[PROCESS] cow_fork: process limit reached ({}/{})",
-            current_count,
-            super::MAX_PROCESSES
+            current_count, max_processes
         );
         return Err(KernelError::ResourceExhausted {
             resource: "process table",
@@ -286,6 +302,7 @@ pub fn cow_fork() -> Result<ProcessId, KernelError> {
 
     let current_process =
         super::current_process().ok_or(KernelError::ProcessNotFound { pid: 0 })?;
+    super::rlimit::check_nproc(current_process)?;
     let current_thread = super::current_thread().ok_or(KernelError::ThreadNotFound { tid: 0 })?;
 
     let new_process = ProcessBuilder::new(format!("{}-cow", current_process.name))
@@ -302,6 +319,7 @@ pub fn cow_fork() -> Result<ProcessId, KernelError> {
 
         // Clone the address space (copies page tables and mapping metadata)
         new_space.clone_from(&current_space)?;
+        super::vdso::map_shared_pages(&mut new_space)?;
 
         // Collect user-space pages for COW marking
         let user_pages = collect_user_pages(&current_space);
@@ -351,8 +369,10 @@ pub fn cow_fork() -> Result<ProcessId, KernelError> {
         }
     }
 
-    // Inherit pgid, sid
+    // Inherit credentials, pgid, sid
     {
+        *new_process.creds.lock() = current_process.credentials();
+
         let parent_pgid = current_process
             .pgid
             .load(core::sync::atomic::Ordering::Acquire);
@@ -365,6 +385,22 @@ pub fn cow_fork() -> Result<ProcessId, KernelError> {
         new_process
             .sid
             .store(parent_sid, core::sync::atomic::Ordering::Release);
+        new_process.wx_exempt.store(
+            current_process
+                .wx_exempt
+                .load(core::sync::atomic::Ordering::Acquire),
+            core::sync::atomic::Ordering::Release,
+        );
+        *new_process.symbols.lock() = current_process.symbols.lock().clone();
+        *new_process.sandbox.lock() = current_process.sandbox.lock().clone();
+        *new_process.syscall_filters.lock() = current_process.syscall_filters.lock().clone();
+        new_process.heap_stats_addr.store(
+            current_process
+                .heap_stats_addr
+                .load(core::sync::atomic::Ordering::Acquire),
+            core::sync::atomic::Ordering::Release,
+        );
+        new_process.rlimits.inherit(&current_process.rlimits);
     }
 
     // Create thread in new process matching current thread
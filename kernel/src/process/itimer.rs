@@ -0,0 +1,358 @@
+//! Interval timers and per-thread CPU time accounting
+//!
+//! On every timer interrupt, [`account_tick`] charges the time elapsed since
+//! the previous tick on this CPU to the interrupted thread. The charge counts
+//! as user time if the interrupt arrived in user mode and as system time
+//! otherwise. The same charge drives the per-process `ITIMER_VIRTUAL` (user
+//! time only) and `ITIMER_PROF` (user + system time) countdowns.
+//! `ITIMER_REAL` counts wall-clock time and is checked against the monotonic
+//! clock on every tick. The same charge is checked against `RLIMIT_CPU`
+//! (see [`super::rlimit`]).
+//!
+//! Each CPU also keeps running user/system/idle totals ([`cpu_times`]) for
+//! `/proc/stat`.
+//!
+//! Expiry posts SIGALRM/SIGVTALRM/SIGPROF with [`Process::send_signal`],
+//! which only touches the atomic pending set and is therefore safe from
+//! interrupt context. Everything on the tick path uses `try_lock` and simply
+//! skips the sample if a lock is contended.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "alloc")]
+use spin::Mutex;
+
+use super::{
+    exit::signals::{SIGALRM, SIGPROF, SIGVTALRM},
+    pcb::Process,
+    thread::Thread,
+    ProcessId, ThreadId,
+};
+
+/// Decrements in real (wall-clock) time; delivers SIGALRM.
+pub const ITIMER_REAL: usize = 0;
+/// Decrements in user CPU time; delivers SIGVTALRM.
+pub const ITIMER_VIRTUAL: usize = 1;
+/// Decrements in user + system CPU time; delivers SIGPROF.
+pub const ITIMER_PROF: usize = 2;
+
+/// A single countdown timer. Values are in microseconds; a value of zero
+/// means disarmed.
+#[derive(Debug, Default)]
+pub struct IntervalTimer {
+    /// Remaining time (VIRTUAL/PROF) or absolute monotonic deadline (REAL)
+    value_us: AtomicU64,
+    /// Reload value after expiry (0 = one-shot)
+    interval_us: AtomicU64,
+}
+
+impl IntervalTimer {
+    pub const fn new() -> Self {
+        Self {
+            value_us: AtomicU64::new(0),
+            interval_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Count down by `us`. Returns `true` if the timer expired, in which case
+    /// it has been reloaded from its interval (or disarmed).
+    fn consume(&self, us: u64) -> bool {
+        let mut expired = false;
+        let _ = self
+            .value_us
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |value| {
+                if value == 0 {
+                    return None;
+                }
+                if value > us {
+                    expired = false;
+                    Some(value - us)
+                } else {
+                    expired = true;
+                    Some(self.interval_us.load(Ordering::Acquire))
+                }
+            });
+        expired
+    }
+}
+
+/// The three POSIX interval timers of a process.
+#[derive(Debug, Default)]
+pub struct IntervalTimers {
+    real: IntervalTimer,
+    virt: IntervalTimer,
+    prof: IntervalTimer,
+}
+
+impl IntervalTimers {
+    pub const fn new() -> Self {
+        Self {
+            real: IntervalTimer::new(),
+            virt: IntervalTimer::new(),
+            prof: IntervalTimer::new(),
+        }
+    }
+
+    fn timer(&self, which: usize) -> Option<&IntervalTimer> {
+        match which {
+            ITIMER_REAL => Some(&self.real),
+            ITIMER_VIRTUAL => Some(&self.virt),
+            ITIMER_PROF => Some(&self.prof),
+            _ => None,
+        }
+    }
+
+    /// Current `(remaining, interval)` of timer `which`, in microseconds.
+    pub fn get(&self, which: usize) -> Option<(u64, u64)> {
+        let timer = self.timer(which)?;
+        let mut value = timer.value_us.load(Ordering::Acquire);
+        if which == ITIMER_REAL && value != 0 {
+            value = value.saturating_sub(now_us()).max(1);
+        }
+        Some((value, timer.interval_us.load(Ordering::Acquire)))
+    }
+
+    /// Arm (or with `value == 0`, disarm) timer `which`. Returns the previous
+    /// `(remaining, interval)`.
+    pub fn set(&self, which: usize, value_us: u64, interval_us: u64) -> Option<(u64, u64)> {
+        let old = self.get(which)?;
+        let timer = self.timer(which)?;
+        let stored = if which == ITIMER_REAL && value_us != 0 {
+            now_us().saturating_add(value_us)
+        } else {
+            value_us
+        };
+        timer.interval_us.store(interval_us, Ordering::Release);
+        timer.value_us.store(stored, Ordering::Release);
+        Some(old)
+    }
+
+    /// Disarm all timers (used when a process exits).
+    pub fn clear(&self) {
+        for timer in [&self.real, &self.virt, &self.prof] {
+            timer.value_us.store(0, Ordering::Release);
+            timer.interval_us.store(0, Ordering::Release);
+        }
+    }
+}
+
+/// Monotonic time in microseconds since boot.
+fn now_us() -> u64 {
+    let tps = crate::arch::timer::hw_ticks_per_second();
+    if tps == 0 {
+        return 0;
+    }
+    (crate::arch::timer::read_hw_timestamp() as u128 * 1_000_000 / tps as u128) as u64
+}
+
+/// Per-CPU timestamp of the last accounting sample.
+static LAST_SAMPLE_US: [AtomicU64; crate::sched::smp::MAX_CPUS] =
+    [const { AtomicU64::new(0) }; crate::sched::smp::MAX_CPUS];
+
+/// Per-CPU totals of the time charged by [`account_tick`], in microseconds:
+/// user, system and idle. Unlike the per-process counters these survive
+/// the processes that were charged, so they only ever grow.
+static CPU_TIME_US: [[AtomicU64; 3]; crate::sched::smp::MAX_CPUS] =
+    [const { [const { AtomicU64::new(0) }; 3] }; crate::sched::smp::MAX_CPUS];
+
+const CPU_USER: usize = 0;
+const CPU_SYSTEM: usize = 1;
+const CPU_IDLE: usize = 2;
+
+/// Clock ticks per second of the CPU times reported by `times()` and the
+/// procfs `stat` files (USER_HZ, `sysconf(_SC_CLK_TCK)`).
+pub const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+/// Convert microseconds of CPU time to clock ticks.
+pub fn us_to_clock_ticks(us: u64) -> u64 {
+    us / (1_000_000 / CLOCK_TICKS_PER_SEC)
+}
+
+/// CPU time spent on one CPU since boot, in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub user_us: u64,
+    pub system_us: u64,
+    /// Samples with no process to charge (idle loop or early boot).
+    pub idle_us: u64,
+}
+
+/// Time charged on `cpu` since boot, or `None` if it has never been
+/// sampled.
+pub fn cpu_times(cpu: usize) -> Option<CpuTimes> {
+    if cpu >= crate::sched::smp::MAX_CPUS || LAST_SAMPLE_US[cpu].load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+    let times = &CPU_TIME_US[cpu];
+    Some(CpuTimes {
+        user_us: times[CPU_USER].load(Ordering::Relaxed),
+        system_us: times[CPU_SYSTEM].load(Ordering::Relaxed),
+        idle_us: times[CPU_IDLE].load(Ordering::Relaxed),
+    })
+}
+
+/// Processes with an armed `ITIMER_REAL`, scanned on every tick.
+#[cfg(feature = "alloc")]
+static REAL_ARMED: Mutex<Vec<ProcessId>> = Mutex::new(Vec::new());
+
+/// Register `pid` for `ITIMER_REAL` expiry checks.
+#[cfg(feature = "alloc")]
+pub fn watch_real(pid: ProcessId) {
+    let mut armed = REAL_ARMED.lock();
+    if !armed.contains(&pid) {
+        armed.push(pid);
+    }
+}
+
+/// The thread running on this CPU, without blocking on any lock.
+#[cfg(feature = "alloc")]
+fn try_current() -> Option<(&'static Process, &'static Thread)> {
+    let (pid, tid) = match crate::sched::SCHEDULER.try_lock()?.current() {
+        // SAFETY: the scheduler keeps `current` pointing to a live Task while
+        // it is installed; we only copy the two IDs out of it.
+        Some(task) => unsafe {
+            let task = task.as_ref();
+            (task.pid, task.tid)
+        },
+        None => (
+            ProcessId(super::BOOT_CURRENT_PID.load(Ordering::Acquire)),
+            ThreadId(super::BOOT_CURRENT_TID.load(Ordering::Acquire)),
+        ),
+    };
+    if pid.0 == 0 {
+        return None;
+    }
+    let process = super::table::PROCESS_TABLE.try_get_process(pid)??;
+    let thread = process.try_get_thread(tid)?;
+    Some((process, thread))
+}
+
+/// Charge the time since the previous tick to the interrupted thread and
+/// run the interval timers.
+///
+/// Called from the timer interrupt handler; `user_mode` is whether the
+/// interrupt arrived while the CPU was executing user code.
+pub fn account_tick(user_mode: bool) {
+    let now = now_us();
+    let cpu = crate::sched::smp::current_cpu_id() as usize % crate::sched::smp::MAX_CPUS;
+    let last = LAST_SAMPLE_US[cpu].swap(now, Ordering::Relaxed);
+
+    #[cfg(feature = "alloc")]
+    {
+        if last != 0 && now > last {
+            let us = now - last;
+            let slot = match try_current() {
+                Some((process, thread)) => {
+                    charge(process, thread, us, user_mode);
+                    if user_mode {
+                        CPU_USER
+                    } else {
+                        CPU_SYSTEM
+                    }
+                }
+                None => CPU_IDLE,
+            };
+            CPU_TIME_US[cpu][slot].fetch_add(us, Ordering::Relaxed);
+        }
+        check_real_timers(now);
+    }
+    #[cfg(not(feature = "alloc"))]
+    let _ = (last, user_mode);
+}
+
+/// Account `us` microseconds of CPU time, run VIRTUAL/PROF countdowns and
+/// enforce `RLIMIT_CPU`.
+#[cfg(feature = "alloc")]
+fn charge(process: &Process, thread: &Thread, us: u64, user_mode: bool) {
+    thread.account_time(us, user_mode);
+    process.account_time(us, user_mode);
+
+    if user_mode && process.itimers.virt.consume(us) {
+        let _ = process.send_signal(SIGVTALRM as usize);
+    }
+    if process.itimers.prof.consume(us) {
+        let _ = process.send_signal(SIGPROF as usize);
+    }
+    super::rlimit::check_cpu(process);
+}
+
+/// Fire every `ITIMER_REAL` whose deadline has passed.
+#[cfg(feature = "alloc")]
+fn check_real_timers(now: u64) {
+    let Some(mut armed) = REAL_ARMED.try_lock() else {
+        return;
+    };
+    armed.retain(|&pid| {
+        let process = match super::table::PROCESS_TABLE.try_get_process(pid) {
+            Some(Some(process)) => process,
+            Some(None) => return false, // process is gone
+            None => return true,        // table busy; retry next tick
+        };
+        let timer = &process.itimers.real;
+        let deadline = timer.value_us.load(Ordering::Acquire);
+        if deadline == 0 || !process.is_alive() {
+            return false;
+        }
+        if now < deadline {
+            return true;
+        }
+
+        let _ = process.send_signal(SIGALRM as usize);
+        let interval = timer.interval_us.load(Ordering::Acquire);
+        if interval == 0 {
+            timer.value_us.store(0, Ordering::Release);
+            return false;
+        }
+        // Skip missed periods rather than firing a burst of catch-ups
+        let missed = (now - deadline) / interval + 1;
+        timer
+            .value_us
+            .store(deadline + missed * interval, Ordering::Release);
+        true
+    });
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consume_one_shot() {
+        let timer = IntervalTimer::new();
+        assert!(!timer.consume(100)); // disarmed
+        timer.value_us.store(250, Ordering::Relaxed);
+        assert!(!timer.consume(100));
+        assert_eq!(timer.value_us.load(Ordering::Relaxed), 150);
+        assert!(timer.consume(200));
+        assert_eq!(timer.value_us.load(Ordering::Relaxed), 0);
+        assert!(!timer.consume(200));
+    }
+
+    #[test]
+    fn test_consume_periodic_reloads() {
+        let timer = IntervalTimer::new();
+        timer.value_us.store(100, Ordering::Relaxed);
+        timer.interval_us.store(1000, Ordering::Relaxed);
+        assert!(timer.consume(100));
+        assert_eq!(timer.value_us.load(Ordering::Relaxed), 1000);
+    }
+
+    #[test]
+    fn test_set_returns_previous() {
+        let timers = IntervalTimers::new();
+        assert_eq!(timers.set(ITIMER_PROF, 5000, 1000), Some((0, 0)));
+        assert_eq!(timers.get(ITIMER_PROF), Some((5000, 1000)));
+        assert_eq!(timers.set(ITIMER_PROF, 0, 0), Some((5000, 1000)));
+        assert_eq!(timers.get(ITIMER_PROF), Some((0, 0)));
+        assert_eq!(timers.get(3), None);
+    }
+
+    #[test]
+    fn test_us_to_clock_ticks() {
+        assert_eq!(us_to_clock_ticks(9_999), 0);
+        assert_eq!(us_to_clock_ticks(1_234_567), 123);
+        assert_eq!(cpu_times(crate::sched::smp::MAX_CPUS), None);
+    }
+}
@@ -24,18 +24,22 @@ use crate::println;
 
 // Re-export submodules
 pub mod creation;
+pub mod creds;
 pub mod cwd;
 pub mod exit;
 pub mod fork;
+pub mod itimer;
 pub mod lifecycle;
 pub mod loader;
 pub mod memory;
 pub mod pcb;
+pub mod rlimit;
 pub mod session;
 pub mod signal_delivery;
 pub mod sync;
 pub mod table;
 pub mod thread;
+pub mod vdso;
 pub mod wait;
 
 // Re-export common types
@@ -47,17 +51,13 @@ pub use thread::{Thread, ThreadId, ThreadState};
 // Re-export thread context types for compatibility
 pub use crate::arch::context::{ArchThreadContext, ThreadContext};
 
-/// Maximum number of concurrent processes (including zombies awaiting reaping).
+/// Capacity of the fixed-size process array used without `alloc`.
 ///
-/// This limit is enforced in fork() to prevent unbounded process table growth
-/// during workloads like BusyBox native compilation (213+ sequential gcc
-/// invocations). Zombie processes count against this limit until reaped by
-/// their parent via waitpid().
+/// With `alloc` the process table grows on demand and the number of
+/// processes (including zombies awaiting reaping) is bounded by the
+/// boot-configured [`crate::limits::max_processes`] instead.
 pub const MAX_PROCESSES: usize = 1024;
 
-/// Maximum threads per process
-pub const MAX_THREADS_PER_PROCESS: usize = 256;
-
 /// Process ID allocator
 static NEXT_PID: AtomicU64 = AtomicU64::new(1);
 
@@ -243,10 +243,8 @@ pub fn exit_thread(exit_code: i32) {
         // Handle CLONE_CHILD_CLEARTID: clear *clear_tid and futex wake
         let clear_ptr = thread.clear_tid.load(core::sync::atomic::Ordering::Acquire);
         if clear_ptr != 0 {
-            unsafe {
-                // Ignore copy_to_user errors here; best effort
-                let _ = crate::syscall::copy_to_user(clear_ptr, &0u32);
-            }
+            // Ignore copy_to_user errors here; best effort
+            let _ = crate::syscall::copy_to_user(clear_ptr, &0u32);
             // Wake futex waiters on that address
             let _ = crate::syscall::sys_futex_wake(clear_ptr, 1, 0);
         }
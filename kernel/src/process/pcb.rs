@@ -3,20 +3,24 @@
 //! The PCB is the core data structure representing a process in the kernel.
 //! It contains all the information needed to manage a process.
 
-use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
 #[cfg(feature = "alloc")]
-use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 
 use spin::Mutex;
 
-use super::thread::{Thread, ThreadId};
+use super::{
+    creds::Credentials,
+    thread::{Thread, ThreadId},
+};
 #[allow(unused_imports)]
 use crate::{
     cap::{CapabilityId, CapabilitySpace},
+    elf::symbols::SymbolMap,
     error::KernelError,
     fs::file::FileTable,
     ipc::EndpointId,
@@ -115,17 +119,32 @@ pub struct Process {
     /// CPU time used (in microseconds)
     pub cpu_time: AtomicU64,
 
+    /// User-mode CPU time of all threads, live and exited (microseconds)
+    pub user_time: AtomicU64,
+
+    /// Kernel-mode CPU time of all threads, live and exited (microseconds)
+    pub system_time: AtomicU64,
+
+    /// User CPU time of reaped children (microseconds)
+    pub children_user_time: AtomicU64,
+
+    /// System CPU time of reaped children (microseconds)
+    pub children_system_time: AtomicU64,
+
+    /// ITIMER_REAL / ITIMER_VIRTUAL / ITIMER_PROF (not inherited on fork)
+    pub itimers: super::itimer::IntervalTimers,
+
+    /// RLIMIT_* soft and hard limits (inherited on fork)
+    pub rlimits: super::rlimit::Rlimits,
+
     /// Memory usage statistics
     pub memory_stats: MemoryStats,
 
     /// Creation timestamp
     pub created_at: u64,
 
-    /// User ID (for future use)
-    pub uid: u32,
-
-    /// Group ID (for future use)
-    pub gid: u32,
+    /// User/group credentials (real, effective, saved, supplementary)
+    pub creds: Mutex<Credentials>,
 
     /// Process group ID (initialized to pid)
     pub pgid: AtomicU64,
@@ -155,6 +174,22 @@ pub struct Process {
     /// Read by sys_exec before enter_usermode to set MSR 0xC0000100.
     pub tls_fs_base: AtomicU64,
 
+    /// Exempt from W^X: may map pages writable and executable (JITs).
+    /// Set by exec from the binary's `PT_WXNEEDED` header; inherited on fork.
+    pub wx_exempt: AtomicBool,
+
+    /// Function symbols of the executable, for fault backtraces.
+    /// Set by exec; inherited on fork.
+    pub symbols: Mutex<Option<Arc<SymbolMap>>>,
+
+    /// Sandbox profile confining the process, if any. Set by exec when
+    /// unset; inherited on fork and never cleared.
+    pub sandbox: Mutex<Option<Arc<crate::security::sandbox::Profile>>>,
+
+    /// Syscall filters checked on every syscall. Only ever added to;
+    /// inherited on fork, kept across exec.
+    pub syscall_filters: Mutex<crate::security::syscall_filter::Filters>,
+
     /// Container ID (0 = not containerized). Inherited by forked children
     /// so processes cannot escape their container namespace.
     pub container_id: AtomicU64,
@@ -166,6 +201,10 @@ pub struct Process {
     /// User-space address of robust futex list head
     /// (set by set_robust_list syscall for cleanup on abnormal exit).
     pub robust_list_head: AtomicU64,
+
+    /// User-space address of the allocator statistics registered with
+    /// SYS_HEAP_PROFILE, read by /proc/<pid>/heap (0 = none).
+    pub heap_stats_addr: AtomicU64,
 }
 
 /// Memory usage statistics
@@ -202,10 +241,15 @@ impl Process {
             children: Mutex::new(Vec::new()),
             exit_code: AtomicU32::new(0),
             cpu_time: AtomicU64::new(0),
+            user_time: AtomicU64::new(0),
+            system_time: AtomicU64::new(0),
+            children_user_time: AtomicU64::new(0),
+            children_system_time: AtomicU64::new(0),
+            itimers: super::itimer::IntervalTimers::new(),
+            rlimits: super::rlimit::Rlimits::new(),
             memory_stats: MemoryStats::default(),
             created_at: crate::arch::timer::get_ticks(),
-            uid: 0,
-            gid: 0,
+            creds: Mutex::new(Credentials::root()),
             pgid: AtomicU64::new(pid.0),
             sid: AtomicU64::new(pid.0),
             env_vars: Mutex::new(BTreeMap::new()),
@@ -214,12 +258,42 @@ impl Process {
             signal_mask: AtomicU64::new(0),
             umask: AtomicU32::new(0o022),
             tls_fs_base: AtomicU64::new(0),
+            wx_exempt: AtomicBool::new(false),
+            symbols: Mutex::new(None),
+            sandbox: Mutex::new(None),
+            syscall_filters: Mutex::new(crate::security::syscall_filter::Filters::new()),
             container_id: AtomicU64::new(0),
             clear_child_tid: AtomicU64::new(0),
             robust_list_head: AtomicU64::new(0),
+            heap_stats_addr: AtomicU64::new(0),
         }
     }
 
+    /// Snapshot of the process credentials
+    pub fn credentials(&self) -> Credentials {
+        *self.creds.lock()
+    }
+
+    /// Real user ID
+    pub fn uid(&self) -> u32 {
+        self.creds.lock().ruid
+    }
+
+    /// Effective user ID (used for permission checks)
+    pub fn euid(&self) -> u32 {
+        self.creds.lock().euid
+    }
+
+    /// Real group ID
+    pub fn gid(&self) -> u32 {
+        self.creds.lock().rgid
+    }
+
+    /// Effective group ID (used for permission checks)
+    pub fn egid(&self) -> u32 {
+        self.creds.lock().egid
+    }
+
     /// Get process state
     pub fn get_state(&self) -> ProcessState {
         match self.state.load(Ordering::Acquire) {
@@ -253,7 +327,7 @@ impl Process {
         let tid = thread.tid;
         let mut threads = self.threads.lock();
 
-        if threads.len() >= super::MAX_THREADS_PER_PROCESS {
+        if threads.len() >= crate::limits::max_threads_per_process() {
             return Err(KernelError::ResourceExhausted {
                 resource: "threads per process",
             });
@@ -267,13 +341,28 @@ impl Process {
         }
 
         threads.insert(tid, thread);
+        drop(threads);
+        super::vdso::sync_thread_id(self);
         Ok(())
     }
 
     /// Remove a thread from this process
     #[cfg(feature = "alloc")]
     pub fn remove_thread(&self, tid: ThreadId) -> Option<Thread> {
-        self.threads.lock().remove(&tid)
+        let thread = self.threads.lock().remove(&tid);
+        super::vdso::sync_thread_id(self);
+        thread
+    }
+
+    /// Get a thread by ID without blocking (`None` if the thread list is
+    /// locked). Safe to call from interrupt context.
+    #[cfg(feature = "alloc")]
+    pub fn try_get_thread(&self, tid: ThreadId) -> Option<&Thread> {
+        // SAFETY: Same reasoning as `get_thread`.
+        unsafe {
+            let threads = self.threads.try_lock()?;
+            threads.get(&tid).map(|t| &*(t as *const Thread))
+        }
     }
 
     /// Get a thread by ID
@@ -313,6 +402,27 @@ impl Process {
         self.cpu_time.load(Ordering::Relaxed)
     }
 
+    /// Charge CPU time as user or system time
+    pub fn account_time(&self, microseconds: u64, user_mode: bool) {
+        self.add_cpu_time(microseconds);
+        if user_mode {
+            self.user_time.fetch_add(microseconds, Ordering::Relaxed);
+        } else {
+            self.system_time.fetch_add(microseconds, Ordering::Relaxed);
+        }
+    }
+
+    /// Fold a reaped child's own and children's CPU time into ours
+    pub fn add_child_times(&self, child: &Process) {
+        let user = child.user_time.load(Ordering::Relaxed)
+            + child.children_user_time.load(Ordering::Relaxed);
+        let system = child.system_time.load(Ordering::Relaxed)
+            + child.children_system_time.load(Ordering::Relaxed);
+        self.children_user_time.fetch_add(user, Ordering::Relaxed);
+        self.children_system_time
+            .fetch_add(system, Ordering::Relaxed);
+    }
+
     /// Set exit code
     pub fn set_exit_code(&self, code: i32) {
         self.exit_code.store(code as u32, Ordering::Release);
@@ -864,8 +974,7 @@ impl ProcessBuilder {
     pub fn build(self) -> Process {
         let pid = super::alloc_pid();
         let mut process = Process::new(pid, self.parent, self.name, self.priority);
-        process.uid = self.uid;
-        process.gid = self.gid;
+        *process.creds.get_mut() = Credentials::new(self.uid, self.gid);
         process
     }
 
@@ -877,8 +986,7 @@ impl ProcessBuilder {
     pub fn build_with_address_space(self) -> Result<Process, KernelError> {
         let pid = super::alloc_pid();
         let mut process = Process::new(pid, self.parent, self.name, self.priority);
-        process.uid = self.uid;
-        process.gid = self.gid;
+        *process.creds.get_mut() = Credentials::new(self.uid, self.gid);
 
         // Initialize the virtual address space with a real page table root
         // and kernel space mappings
@@ -0,0 +1,262 @@
+//! Per-process resource limits (POSIX getrlimit / setrlimit)
+//!
+//! Every process has a soft and a hard limit for each `RLIMIT_*` resource.
+//! They are inherited on fork, kept across exec, and enforced where the
+//! resource is consumed:
+//!
+//! - `RLIMIT_AS` / `RLIMIT_DATA`: mmap and brk (`syscall::memory`)
+//! - `RLIMIT_NOFILE`: descriptor allocation in the file table
+//! - `RLIMIT_NPROC`: fork, counting the processes of the caller's real user
+//! - `RLIMIT_CPU`: the timer tick ([`super::itimer::account_tick`]) posts
+//!   SIGXCPU at the soft limit and every second after it, and SIGKILL at the
+//!   hard limit
+//!
+//! The system-wide maxima in [`crate::limits`] still apply on top: a process
+//! never gets more descriptors or processes than those allow, whatever its
+//! own limits say. Only root may raise a hard limit.
+//!
+//! The limits are atomics so the tick path can read them without a lock.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{
+    exit::signals::{SIGKILL, SIGXCPU},
+    pcb::Process,
+};
+use crate::error::KernelError;
+
+/// CPU time in seconds.
+pub const RLIMIT_CPU: usize = 0;
+/// Largest file that may be created, in bytes.
+pub const RLIMIT_FSIZE: usize = 1;
+/// Heap (data segment) size in bytes.
+pub const RLIMIT_DATA: usize = 2;
+/// Main thread stack size in bytes.
+pub const RLIMIT_STACK: usize = 3;
+/// Core dump size in bytes.
+pub const RLIMIT_CORE: usize = 4;
+/// Processes of the real user ID.
+pub const RLIMIT_NPROC: usize = 6;
+/// Open file descriptors.
+pub const RLIMIT_NOFILE: usize = 7;
+/// Address space size in bytes.
+pub const RLIMIT_AS: usize = 9;
+
+/// Number of resource slots (matches Linux, so `prlimit` tools work).
+pub const RLIM_NLIMITS: usize = 16;
+
+/// No limit.
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+/// Default soft stack limit; the hard limit is unlimited.
+const DEFAULT_STACK: u64 = 8 * 1024 * 1024;
+
+/// Soft and hard limits of one process.
+#[derive(Debug)]
+pub struct Rlimits {
+    cur: [AtomicU64; RLIM_NLIMITS],
+    max: [AtomicU64; RLIM_NLIMITS],
+    /// CPU second at which the next SIGXCPU is due (0 = at the soft limit)
+    xcpu_next: AtomicU64,
+}
+
+impl Default for Rlimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rlimits {
+    /// Limits of a fresh process: everything unlimited except the soft
+    /// stack limit.
+    pub fn new() -> Self {
+        let limits = Self {
+            cur: [const { AtomicU64::new(RLIM_INFINITY) }; RLIM_NLIMITS],
+            max: [const { AtomicU64::new(RLIM_INFINITY) }; RLIM_NLIMITS],
+            xcpu_next: AtomicU64::new(0),
+        };
+        limits.cur[RLIMIT_STACK].store(DEFAULT_STACK, Ordering::Relaxed);
+        limits
+    }
+
+    /// `(soft, hard)` limit of `resource`, as set.
+    pub fn get(&self, resource: usize) -> Option<(u64, u64)> {
+        if resource >= RLIM_NLIMITS {
+            return None;
+        }
+        Some((
+            self.cur[resource].load(Ordering::Relaxed),
+            self.max[resource].load(Ordering::Relaxed),
+        ))
+    }
+
+    /// `(soft, hard)` limit of `resource` in force, with the system-wide
+    /// maxima applied.
+    pub fn effective(&self, resource: usize) -> Option<(u64, u64)> {
+        let (cur, max) = self.get(resource)?;
+        let system = match resource {
+            RLIMIT_NOFILE => crate::limits::max_open_files() as u64,
+            RLIMIT_NPROC => crate::limits::max_processes() as u64,
+            _ => RLIM_INFINITY,
+        };
+        Some((cur.min(system), max.min(system)))
+    }
+
+    /// Soft limit of `resource` in force (unlimited for unknown resources).
+    pub fn soft(&self, resource: usize) -> u64 {
+        self.effective(resource)
+            .map_or(RLIM_INFINITY, |(cur, _)| cur)
+    }
+
+    /// Set the limits of `resource`. Raising the hard limit needs
+    /// `privileged` (root).
+    pub fn set(
+        &self,
+        resource: usize,
+        cur: u64,
+        max: u64,
+        privileged: bool,
+    ) -> Result<(), KernelError> {
+        let (_, old_max) = self.get(resource).ok_or(KernelError::InvalidArgument {
+            name: "resource",
+            value: "unknown RLIMIT_*",
+        })?;
+        if cur > max {
+            return Err(KernelError::InvalidArgument {
+                name: "rlimit",
+                value: "soft limit above hard limit",
+            });
+        }
+        if max > old_max && !privileged {
+            return Err(KernelError::PermissionDenied {
+                operation: "raise hard resource limit",
+            });
+        }
+        self.cur[resource].store(cur, Ordering::Relaxed);
+        self.max[resource].store(max, Ordering::Relaxed);
+        if resource == RLIMIT_CPU {
+            self.xcpu_next.store(0, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Copy `parent`'s limits (fork).
+    pub fn inherit(&self, parent: &Rlimits) {
+        for resource in 0..RLIM_NLIMITS {
+            self.cur[resource].store(
+                parent.cur[resource].load(Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+            self.max[resource].store(
+                parent.max[resource].load(Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+        }
+        self.xcpu_next.store(0, Ordering::Relaxed);
+    }
+
+    /// Signal owed once the process has used `cpu_us` of CPU time:
+    /// SIGKILL past the hard `RLIMIT_CPU`, SIGXCPU at the soft limit and
+    /// then once per further second.
+    pub fn cpu_signal(&self, cpu_us: u64) -> Option<i32> {
+        let secs = cpu_us / 1_000_000;
+        if secs >= self.max[RLIMIT_CPU].load(Ordering::Relaxed) {
+            return Some(SIGKILL);
+        }
+        let cur = self.cur[RLIMIT_CPU].load(Ordering::Relaxed);
+        let next = self.xcpu_next.load(Ordering::Relaxed);
+        if secs < cur || secs < next {
+            return None;
+        }
+        self.xcpu_next.store(secs + 1, Ordering::Relaxed);
+        Some(SIGXCPU)
+    }
+}
+
+/// Descriptor limit of the calling process: its soft `RLIMIT_NOFILE`
+/// capped by the system-wide maximum.
+pub fn max_open_files() -> usize {
+    match super::current_process() {
+        Some(process) => process.rlimits.soft(RLIMIT_NOFILE) as usize,
+        None => crate::limits::max_open_files(),
+    }
+}
+
+/// Refuse a fork that would take `process`'s real user past its
+/// `RLIMIT_NPROC`. Root is exempt.
+#[cfg(feature = "alloc")]
+pub fn check_nproc(process: &Process) -> Result<(), KernelError> {
+    let uid = process.uid();
+    let limit = process.rlimits.soft(RLIMIT_NPROC);
+    if uid == 0 || limit == RLIM_INFINITY {
+        return Ok(());
+    }
+    let mut count = 0u64;
+    super::table::PROCESS_TABLE.for_each(|p| {
+        if p.uid() == uid {
+            count += 1;
+        }
+    });
+    if count >= limit {
+        return Err(KernelError::ResourceExhausted {
+            resource: "RLIMIT_NPROC",
+        });
+    }
+    Ok(())
+}
+
+/// Enforce `RLIMIT_CPU` after charging CPU time to `process`. Called from
+/// the timer tick.
+pub fn check_cpu(process: &Process) {
+    let used =
+        process.user_time.load(Ordering::Relaxed) + process.system_time.load(Ordering::Relaxed);
+    if let Some(signal) = process.rlimits.cpu_signal(used) {
+        let _ = process.send_signal(signal as usize);
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let limits = Rlimits::new();
+        assert_eq!(limits.get(RLIMIT_AS), Some((RLIM_INFINITY, RLIM_INFINITY)));
+        assert_eq!(
+            limits.get(RLIMIT_STACK),
+            Some((DEFAULT_STACK, RLIM_INFINITY))
+        );
+        assert_eq!(limits.get(RLIM_NLIMITS), None);
+    }
+
+    #[test]
+    fn test_set_and_inherit() {
+        let limits = Rlimits::new();
+        assert!(limits.set(RLIMIT_AS, 2, 1, false).is_err());
+        limits.set(RLIMIT_AS, 1 << 20, 1 << 30, false).unwrap();
+        // Lowering is always allowed, raising the hard limit is not
+        limits.set(RLIMIT_AS, 1 << 20, 1 << 29, false).unwrap();
+        assert!(limits.set(RLIMIT_AS, 1 << 20, 1 << 30, false).is_err());
+        limits.set(RLIMIT_AS, 1 << 20, 1 << 30, true).unwrap();
+        assert!(limits.set(RLIM_NLIMITS, 0, 0, true).is_err());
+
+        let child = Rlimits::new();
+        child.inherit(&limits);
+        assert_eq!(child.get(RLIMIT_AS), Some((1 << 20, 1 << 30)));
+    }
+
+    #[test]
+    fn test_cpu_signal() {
+        let limits = Rlimits::new();
+        assert_eq!(limits.cpu_signal(u64::MAX), None);
+
+        limits.set(RLIMIT_CPU, 2, 4, false).unwrap();
+        assert_eq!(limits.cpu_signal(1_999_999), None);
+        assert_eq!(limits.cpu_signal(2_000_000), Some(SIGXCPU));
+        // Once per second past the soft limit
+        assert_eq!(limits.cpu_signal(2_500_000), None);
+        assert_eq!(limits.cpu_signal(3_000_000), Some(SIGXCPU));
+        assert_eq!(limits.cpu_signal(4_000_000), Some(SIGKILL));
+    }
+}
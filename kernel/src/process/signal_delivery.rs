@@ -36,8 +36,8 @@
 #[allow(unused_imports)]
 use crate::{error::KernelError, println, process::pcb::Process, process::thread::Thread};
 
-/// Syscall number for SIG_RETURN (must match Syscall::SigReturn = 123).
-const SYS_SIGRETURN: u64 = 123;
+/// Syscall number for SIG_RETURN.
+const SYS_SIGRETURN: u64 = veridian_abi::nr::SYS_SIGRETURN as u64;
 
 /// Signal handler value indicating default action.
 const SIG_DFL: u64 = 0;
@@ -121,6 +121,10 @@ const SIGRETURN_TRAMPOLINE: [u8; 15] = [
     0x0f, 0x0b, // ud2
 ];
 
+// The `mov rax, 123` immediate above is SYS_SIGRETURN.
+#[cfg(target_arch = "x86_64")]
+const _: () = assert!(SYS_SIGRETURN == 0x7b);
+
 /// Size of the trampoline code in bytes.
 #[cfg(target_arch = "x86_64")]
 const TRAMPOLINE_SIZE: usize = SIGRETURN_TRAMPOLINE.len();
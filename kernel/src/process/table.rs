@@ -182,6 +182,21 @@ impl ProcessTable {
         })
     }
 
+    /// Get a process by PID without blocking.
+    ///
+    /// Returns `None` if the table is locked, otherwise the lookup result.
+    /// Used from interrupt context, where spinning on the table lock could
+    /// deadlock against the interrupted code.
+    #[cfg(feature = "alloc")]
+    pub fn try_get_process(&self, pid: ProcessId) -> Option<Option<&'static Process>> {
+        let entries = self.entries.try_lock()?;
+
+        Some(entries.get(&pid).map(|entry| {
+            // SAFETY: Same reasoning as `get_process`.
+            unsafe { &*(entry.process.as_ref() as *const Process) }
+        }))
+    }
+
     /// Get a process by PID (no-alloc version)
     #[cfg(not(feature = "alloc"))]
     pub fn get_process(&self, pid: ProcessId) -> Option<&'static Process> {
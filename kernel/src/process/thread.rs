@@ -270,7 +270,7 @@ impl ThreadLocalStorage {
     ///
     /// On x86_64, sets FS base (via WRFSBASE or MSR).
     /// On AArch64, sets TPIDR_EL0.
-    /// On RISC-V, sets the `tp` register.
+    /// On RISC-V, does nothing: `tp` is loaded on return to user mode.
     ///
     /// This should be called during context switch or thread initialization
     /// to point the hardware TLS register to this thread's TLS area.
@@ -311,17 +311,9 @@ impl ThreadLocalStorage {
             }
         }
 
-        #[cfg(target_arch = "riscv64")]
-        {
-            // Set tp (thread pointer) register
-            // SAFETY: Writing the `tp` register sets the thread pointer used for
-            // TLS access. `self.base` is a valid address from the frame allocator.
-            // The `tp` register is a general-purpose register designated by the
-            // RISC-V ABI for TLS, accessible in both S-mode and U-mode.
-            unsafe {
-                core::arch::asm!("mv tp, {}", in(reg) self.base);
-            }
-        }
+        // RISC-V: tp holds the per-CPU area while in the kernel (see
+        // sched::percpu). The thread's TLS base is kept in its context and
+        // only goes into tp on the way back to user mode.
     }
 }
 
@@ -370,6 +362,12 @@ pub struct Thread {
     /// Total CPU time used (microseconds)
     pub cpu_time: AtomicU64,
 
+    /// CPU time spent in user mode (microseconds)
+    pub user_time: AtomicU64,
+
+    /// CPU time spent in the kernel on this thread's behalf (microseconds)
+    pub system_time: AtomicU64,
+
     /// Wake up time (for sleeping threads)
     pub wake_time: AtomicU64,
 
@@ -494,6 +492,8 @@ impl Thread {
             current_cpu: AtomicU32::new(u32::MAX),
             time_slice: AtomicU32::new(10), // Default time slice
             cpu_time: AtomicU64::new(0),
+            user_time: AtomicU64::new(0),
+            system_time: AtomicU64::new(0),
             wake_time: AtomicU64::new(0),
             exit_code: AtomicU32::new(0),
             priority: 2, // Normal priority
@@ -578,6 +578,16 @@ impl Thread {
         self.cpu_time.fetch_add(microseconds, Ordering::Relaxed);
     }
 
+    /// Charge CPU time as user or system time
+    pub fn account_time(&self, microseconds: u64, user_mode: bool) {
+        self.add_cpu_time(microseconds);
+        if user_mode {
+            self.user_time.fetch_add(microseconds, Ordering::Relaxed);
+        } else {
+            self.system_time.fetch_add(microseconds, Ordering::Relaxed);
+        }
+    }
+
     /// Set scheduler task pointer
     pub fn set_task_ptr(&self, task: Option<NonNull<Task>>) {
         self.task_ptr.lock().0 = task;
@@ -691,6 +701,8 @@ impl Thread {
         self.state
             .store(ThreadState::Ready as u32, Ordering::Release);
         self.cpu_time.store(0, Ordering::Relaxed);
+        self.user_time.store(0, Ordering::Relaxed);
+        self.system_time.store(0, Ordering::Relaxed);
         self.time_slice.store(10, Ordering::Relaxed); // Default time slice
     }
 
@@ -0,0 +1,440 @@
+//! Virtual dynamic shared object (vDSO)
+//!
+//! Three pages are mapped at [`VDSO_BASE`] in every user address space:
+//!
+//! | Offset   | Contents                                 | Access |
+//! |----------|------------------------------------------|--------|
+//! | `0x0000` | [`VdsoData`], shared by all processes    | R      |
+//! | `0x1000` | [`VdsoProcData`], private to the process | R      |
+//! | `0x2000` | Code                                     | R+X    |
+//!
+//! The code page starts with a [`VdsoHeader`] giving the offsets of
+//! `clock_gettime`, `gettid` and `sched_yield`. None of them trap: each
+//! answers from the data pages or returns `-ENOSYS` (-1, as for
+//! `SyscallError::InvalidSyscall`), telling the caller to make the real
+//! syscall instead (also the answer for clocks other than `CLOCK_REALTIME`
+//! and `CLOCK_MONOTONIC`). The base address reaches user space as the
+//! `AT_VDSO` auxiliary vector entry.
+//!
+//! The code only addresses the data pages relative to its own position, so
+//! the kernel copies the assembled blob into a frame once and maps that same
+//! frame into every process.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::pcb::Process;
+use crate::{
+    error::KernelError,
+    mm::{FrameNumber, PageFlags, VirtualAddressSpace, PAGE_SIZE},
+};
+
+/// User address of the vDSO, below the lowest randomized stack position.
+pub const VDSO_BASE: usize = 0x7FFF_E000_0000;
+/// User address of the shared [`VdsoData`] page.
+pub const VDSO_DATA: usize = VDSO_BASE;
+/// User address of the per-process [`VdsoProcData`] page.
+pub const VDSO_PROC_DATA: usize = VDSO_BASE + PAGE_SIZE;
+/// User address of the code page (and its [`VdsoHeader`]).
+pub const VDSO_TEXT: usize = VDSO_BASE + 2 * PAGE_SIZE;
+
+/// `"VDSO"` in little-endian byte order.
+pub const VDSO_MAGIC: u32 = 0x4F53_4456;
+/// Layout version of [`VdsoHeader`] and the data pages.
+pub const VDSO_VERSION: u32 = 1;
+
+/// Data shared by every process, updated by the kernel.
+#[repr(C)]
+pub struct VdsoData {
+    /// Frequency of the user-readable hardware counter in Hz (0 when the
+    /// counter cannot be read from user mode).
+    pub counter_hz: AtomicU64,
+    /// Tasks waiting in the ready queues at the last scheduler tick.
+    /// `u64::MAX` when unknown.
+    pub ready_tasks: AtomicU64,
+}
+
+/// Data private to one process.
+#[repr(C)]
+pub struct VdsoProcData {
+    /// Thread ID of the only thread, or 0 once the process has several.
+    pub tid: u64,
+}
+
+/// Header at the start of the code page.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VdsoHeader {
+    /// [`VDSO_MAGIC`]
+    pub magic: u32,
+    /// [`VDSO_VERSION`]
+    pub version: u32,
+    /// Offset of `long clock_gettime(clockid_t, struct timespec *)`
+    pub clock_gettime: u32,
+    /// Offset of `long gettid(void)`
+    pub gettid: u32,
+    /// Offset of `long sched_yield(void)`
+    pub sched_yield: u32,
+    /// Reserved, zero
+    pub reserved: u32,
+}
+
+// The code blob. Data pages are found relative to `__vdso_text_start`, which
+// becomes VDSO_TEXT once copied, so the blob is position independent.
+#[cfg(target_arch = "x86_64")]
+core::arch::global_asm!(
+    ".pushsection .rodata.vdso, \"a\"",
+    ".balign 16",
+    ".globl __vdso_text_start",
+    "__vdso_text_start:",
+    ".long 0x4F534456",
+    ".long 1",
+    ".long .Lvdso_clock_gettime - __vdso_text_start",
+    ".long .Lvdso_gettid - __vdso_text_start",
+    ".long .Lvdso_sched_yield - __vdso_text_start",
+    ".long 0",
+    // rdi = clock id, rsi = struct timespec *
+    ".Lvdso_clock_gettime:",
+    "cmp rdi, 1",
+    "ja .Lvdso_enosys",
+    "mov rcx, qword ptr [rip + __vdso_text_start - 0x2000]",
+    "test rcx, rcx",
+    "jz .Lvdso_enosys",
+    "rdtsc",
+    "shl rdx, 32",
+    "or rax, rdx",
+    "xor edx, edx",
+    "div rcx",
+    "mov qword ptr [rsi], rax",
+    "imul rax, rdx, 1000000000",
+    "xor edx, edx",
+    "div rcx",
+    "mov qword ptr [rsi + 8], rax",
+    "xor eax, eax",
+    "ret",
+    ".Lvdso_gettid:",
+    "mov rax, qword ptr [rip + __vdso_text_start - 0x1000]",
+    "test rax, rax",
+    "jz .Lvdso_enosys",
+    "ret",
+    ".Lvdso_sched_yield:",
+    "mov rax, qword ptr [rip + __vdso_text_start - 0x2000 + 8]",
+    "test rax, rax",
+    "jnz .Lvdso_enosys",
+    "pause",
+    "ret",
+    ".Lvdso_enosys:",
+    "mov rax, -1",
+    "ret",
+    ".globl __vdso_text_end",
+    "__vdso_text_end:",
+    ".popsection",
+);
+
+#[cfg(target_arch = "aarch64")]
+core::arch::global_asm!(
+    ".pushsection .rodata.vdso, \"a\"",
+    ".balign 16",
+    ".globl __vdso_text_start",
+    "__vdso_text_start:",
+    ".long 0x4F534456",
+    ".long 1",
+    ".long .Lvdso_clock_gettime - __vdso_text_start",
+    ".long .Lvdso_gettid - __vdso_text_start",
+    ".long .Lvdso_sched_yield - __vdso_text_start",
+    ".long 0",
+    // x0 = clock id, x1 = struct timespec *
+    ".Lvdso_clock_gettime:",
+    "cmp x0, #1",
+    "b.hi .Lvdso_enosys",
+    "adr x9, __vdso_text_start",
+    "sub x9, x9, #0x2000",
+    "ldr x10, [x9]",
+    "cbz x10, .Lvdso_enosys",
+    "isb",
+    "mrs x11, cntvct_el0",
+    "udiv x12, x11, x10",
+    "msub x13, x12, x10, x11",
+    "movz x14, #0xca00",
+    "movk x14, #0x3b9a, lsl #16",
+    "mul x13, x13, x14",
+    "udiv x13, x13, x10",
+    "stp x12, x13, [x1]",
+    "mov x0, #0",
+    "ret",
+    ".Lvdso_gettid:",
+    "adr x9, __vdso_text_start",
+    "sub x9, x9, #0x1000",
+    "ldr x0, [x9]",
+    "cbz x0, .Lvdso_enosys",
+    "ret",
+    ".Lvdso_sched_yield:",
+    "adr x9, __vdso_text_start",
+    "sub x9, x9, #0x2000",
+    "ldr x10, [x9, #8]",
+    "cbnz x10, .Lvdso_enosys",
+    "yield",
+    "mov x0, #0",
+    "ret",
+    ".Lvdso_enosys:",
+    "mov x0, #-1",
+    "ret",
+    ".globl __vdso_text_end",
+    "__vdso_text_end:",
+    ".popsection",
+);
+
+#[cfg(target_arch = "riscv64")]
+core::arch::global_asm!(
+    ".pushsection .rodata.vdso, \"a\"",
+    ".balign 16",
+    ".globl __vdso_text_start",
+    "__vdso_text_start:",
+    ".long 0x4F534456",
+    ".long 1",
+    ".long .Lvdso_clock_gettime - __vdso_text_start",
+    ".long .Lvdso_gettid - __vdso_text_start",
+    ".long .Lvdso_sched_yield - __vdso_text_start",
+    ".long 0",
+    // a0 = clock id, a1 = struct timespec *
+    ".Lvdso_clock_gettime:",
+    "li t0, 1",
+    "bgtu a0, t0, .Lvdso_enosys",
+    "lla t1, __vdso_text_start",
+    "li t0, 0x2000",
+    "sub t1, t1, t0",
+    "ld t2, 0(t1)",
+    "beqz t2, .Lvdso_enosys",
+    "rdtime t3",
+    "divu t4, t3, t2",
+    "remu t5, t3, t2",
+    "li t6, 1000000000",
+    "mul t5, t5, t6",
+    "divu t5, t5, t2",
+    "sd t4, 0(a1)",
+    "sd t5, 8(a1)",
+    "li a0, 0",
+    "ret",
+    ".Lvdso_gettid:",
+    "lla t1, __vdso_text_start",
+    "li t0, 0x1000",
+    "sub t1, t1, t0",
+    "ld a0, 0(t1)",
+    "beqz a0, .Lvdso_enosys",
+    "ret",
+    ".Lvdso_sched_yield:",
+    "lla t1, __vdso_text_start",
+    "li t0, 0x2000",
+    "sub t1, t1, t0",
+    "ld t2, 8(t1)",
+    "bnez t2, .Lvdso_enosys",
+    "li a0, 0",
+    "ret",
+    ".Lvdso_enosys:",
+    "li a0, -1",
+    "ret",
+    ".globl __vdso_text_end",
+    "__vdso_text_end:",
+    ".popsection",
+);
+
+extern "C" {
+    static __vdso_text_start: u8;
+    static __vdso_text_end: u8;
+}
+
+/// The assembled code page contents.
+fn text_blob() -> &'static [u8] {
+    // SAFETY: Both symbols are defined by the global_asm! block above and
+    // delimit one contiguous, read-only byte range in .rodata.
+    unsafe {
+        let start = core::ptr::addr_of!(__vdso_text_start);
+        let end = core::ptr::addr_of!(__vdso_text_end);
+        core::slice::from_raw_parts(start, end as usize - start as usize)
+    }
+}
+
+/// Frames backing the shared pages.
+struct VdsoPages {
+    data: FrameNumber,
+    text: FrameNumber,
+}
+
+impl VdsoPages {
+    fn data(&self) -> &'static VdsoData {
+        let virt = crate::mm::phys_to_virt_addr(self.data.as_u64() << 12);
+        // SAFETY: The frame was zeroed and dedicated to VdsoData in
+        // `allocate()` and is never freed. VdsoData is all atomics, so
+        // shared references may be used concurrently.
+        unsafe { &*(virt as *const VdsoData) }
+    }
+}
+
+static PAGES: spin::Once<VdsoPages> = spin::Once::new();
+
+/// Allocate and fill the shared frames.
+fn allocate() -> Result<VdsoPages, KernelError> {
+    let blob = text_blob();
+    if blob.len() > PAGE_SIZE {
+        return Err(KernelError::InvalidState {
+            expected: "vDSO code within one page",
+            actual: "vDSO code larger than a page",
+        });
+    }
+
+    let alloc_frame = || {
+        crate::mm::frame_allocator::per_cpu_alloc_frame().map_err(|_| KernelError::OutOfMemory {
+            requested: PAGE_SIZE,
+            available: 0,
+        })
+    };
+    let data = alloc_frame()?;
+    let text = alloc_frame()?;
+
+    // SAFETY: Both frames were just allocated and are reachable through the
+    // physical memory window; nothing else references them yet.
+    unsafe {
+        let data_virt = crate::mm::phys_to_virt_addr(data.as_u64() << 12) as *mut u8;
+        core::ptr::write_bytes(data_virt, 0, PAGE_SIZE);
+        let text_virt = crate::mm::phys_to_virt_addr(text.as_u64() << 12) as *mut u8;
+        core::ptr::write_bytes(text_virt, 0, PAGE_SIZE);
+        core::ptr::copy_nonoverlapping(blob.as_ptr(), text_virt, blob.len());
+    }
+
+    enable_user_counter();
+    let pages = VdsoPages { data, text };
+    let data = pages.data();
+    data.counter_hz
+        .store(crate::arch::timer::hw_ticks_per_second(), Ordering::Release);
+    data.ready_tasks.store(u64::MAX, Ordering::Release);
+    Ok(pages)
+}
+
+/// Let user mode read the counter the vDSO's `clock_gettime` uses.
+fn enable_user_counter() {
+    // RDTSC is available to user mode unless CR4.TSD is set, which we never
+    // do.
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: Setting CNTKCTL_EL1.EL0VCTEN only grants EL0 read access to
+    // CNTVCT_EL0.
+    unsafe {
+        let cntkctl: u64;
+        core::arch::asm!("mrs {}, CNTKCTL_EL1", out(reg) cntkctl);
+        core::arch::asm!("msr CNTKCTL_EL1, {}", in(reg) cntkctl | 0b10);
+    }
+
+    #[cfg(target_arch = "riscv64")]
+    // SAFETY: Setting scounteren.TM only lets U-mode execute `rdtime`.
+    unsafe {
+        core::arch::asm!("csrs scounteren, {}", in(reg) 0b10u64);
+    }
+}
+
+/// Map the shared vDSO pages into an address space.
+///
+/// The frames are not recorded as mappings, so [`VirtualAddressSpace::clear`]
+/// and `fork` neither free nor copy them; fork calls this again for the
+/// child.
+pub fn map_shared_pages(memory_space: &mut VirtualAddressSpace) -> Result<(), KernelError> {
+    let pages = PAGES.try_call_once(allocate)?;
+    memory_space.map_page_with_frame(
+        VDSO_DATA,
+        pages.data,
+        PageFlags::PRESENT | PageFlags::USER | PageFlags::NO_EXECUTE,
+    )?;
+    memory_space.map_page_with_frame(VDSO_TEXT, pages.text, PageFlags::PRESENT | PageFlags::USER)
+}
+
+/// Map the whole vDSO into a fresh address space.
+pub fn install(memory_space: &mut VirtualAddressSpace) -> Result<(), KernelError> {
+    map_shared_pages(memory_space)?;
+    // A recorded page: freed with the address space, deep-copied by fork
+    memory_space.map_page(
+        VDSO_PROC_DATA,
+        PageFlags::PRESENT | PageFlags::USER | PageFlags::NO_EXECUTE,
+    )
+}
+
+/// Publish the process's thread ID for the vDSO's `gettid`.
+///
+/// Only a single-threaded process gets a fast path; with more threads the
+/// page cannot tell them apart and `gettid` falls back to the syscall.
+pub fn sync_thread_id(process: &Process) {
+    let tid = {
+        let threads = process.threads.lock();
+        match threads.len() {
+            1 => threads.keys().next().map_or(0, |tid| tid.0),
+            _ => 0,
+        }
+    };
+    let memory_space = process.memory_space.lock();
+    // Kernel-only processes have no vDSO page to update
+    let _ =
+        crate::elf::write_to_user_pages(&memory_space, VDSO_PROC_DATA as u64, &tid.to_ne_bytes());
+}
+
+/// Publish the number of tasks waiting to run, read by the vDSO's
+/// `sched_yield`.
+pub fn publish_ready_tasks(count: u64) {
+    if let Some(pages) = PAGES.get() {
+        pages.data().ready_tasks.store(count, Ordering::Relaxed);
+    }
+}
+
+/// Split a counter reading into seconds and nanoseconds, as the vDSO does.
+pub fn ticks_to_timespec(ticks: u64, hz: u64) -> (u64, u64) {
+    if hz == 0 {
+        return (0, 0);
+    }
+    (ticks / hz, (ticks % hz) * 1_000_000_000 / hz)
+}
+
+/// Time since boot as `(seconds, nanoseconds)`, from the same counter the
+/// vDSO reads so that both paths agree.
+pub fn monotonic_time() -> (u64, u64) {
+    ticks_to_timespec(
+        crate::arch::timer::read_hw_timestamp(),
+        crate::arch::timer::hw_ticks_per_second(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_points_into_blob() {
+        let blob = text_blob();
+        assert!(blob.len() >= core::mem::size_of::<VdsoHeader>());
+        assert!(blob.len() <= PAGE_SIZE);
+
+        // SAFETY: The blob starts with a VdsoHeader-shaped table of u32s.
+        let header = unsafe { core::ptr::read_unaligned(blob.as_ptr() as *const VdsoHeader) };
+        assert_eq!(header.magic, VDSO_MAGIC);
+        assert_eq!(header.version, VDSO_VERSION);
+        let header_len = core::mem::size_of::<VdsoHeader>() as u32;
+        for offset in [header.clock_gettime, header.gettid, header.sched_yield] {
+            assert!(offset >= header_len);
+            assert!((offset as usize) < blob.len());
+        }
+    }
+
+    #[test]
+    fn test_ticks_to_timespec() {
+        assert_eq!(ticks_to_timespec(0, 1_000), (0, 0));
+        assert_eq!(ticks_to_timespec(2_500, 1_000), (2, 500_000_000));
+        assert_eq!(
+            ticks_to_timespec(5_000_000_001, 2_000_000_000),
+            (2, 500_000_000)
+        );
+        assert_eq!(ticks_to_timespec(123, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_layout_is_below_stack() {
+        use crate::security::memory_protection::USER_STACK_TOP;
+
+        assert_eq!(VDSO_BASE % PAGE_SIZE, 0);
+        // The stack slides down at most 256 MiB from USER_STACK_TOP
+        assert!(VDSO_TEXT + PAGE_SIZE < USER_STACK_TOP - (1 << 28) - (64 << 20));
+    }
+}
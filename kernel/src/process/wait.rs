@@ -323,9 +323,13 @@ pub fn notify_parent(child_pid: ProcessId, status: WaitStatus) {
 /// process table.
 #[cfg(feature = "alloc")]
 pub fn collect_zombie(child_pid: ProcessId, parent_pid: ProcessId) -> Result<(), KernelError> {
-    // Remove from parent's children list.
+    // Remove from parent's children list and charge the child's CPU time to
+    // the parent's RUSAGE_CHILDREN totals.
     if let Some(parent) = super::table::get_process(parent_pid) {
         parent.children.lock().retain(|&p| p != child_pid);
+        if let Some(child) = super::table::get_process(child_pid) {
+            parent.add_child_times(child);
+        }
     }
 
     // Remove from the process table.
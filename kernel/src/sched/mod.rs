@@ -12,6 +12,7 @@
 //! - [`task`] - Task control block and priority types
 //! - [`metrics`] - Performance metrics and measurement
 //! - [`smp`] - Symmetric multiprocessing support
+//! - [`percpu`] - Per-CPU variables and the per-CPU base register
 //! - [`queue`] - Ready queue management
 //! - [`numa`] - NUMA-aware scheduling
 //! - [`init`] - Initialization and timer setup
@@ -20,6 +21,7 @@
 //! - [`ipc_blocking`] - IPC blocking/waking and wait queues
 //! - [`task_management`] - Task creation, exit, and thread scheduling
 //! - [`load_balance`] - Load balancing and task migration
+//! - [`workqueue`] - Deferred work run by per-CPU kworker tasks
 
 #![allow(dead_code, function_casts_as_integer)]
 
@@ -31,6 +33,7 @@ pub mod ipc_blocking;
 pub mod load_balance;
 pub mod metrics;
 pub mod numa;
+pub mod percpu;
 pub mod percpu_queue;
 pub mod process_compat;
 pub mod queue;
@@ -40,6 +43,7 @@ pub mod smp;
 pub mod task;
 pub mod task_management;
 pub mod task_ptr;
+pub mod workqueue;
 
 #[cfg(target_arch = "riscv64")]
 pub mod riscv_scheduler;
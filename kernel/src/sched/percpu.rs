@@ -0,0 +1,270 @@
+//! Per-CPU variables
+//!
+//! Every CPU owns a [`CpuArea`] and keeps its address in a register: the GS
+//! base on x86_64, `TPIDR_EL1` on AArch64 and `tp` on RISC-V. [`cpu_id`]
+//! reads the current CPU's number through that register, which is a load or
+//! two instead of a CPUID, MPIDR or mhartid round trip.
+//!
+//! [`define_per_cpu!`](crate::define_per_cpu) declares a [`PerCpu`] with one
+//! cache-line-aligned slot per CPU. [`PerCpu::this_cpu`] returns the slot of
+//! the CPU the caller is running on; the other slots stay reachable through
+//! [`PerCpu::on_cpu`] and [`PerCpu::iter`], so slot types must be `Sync`
+//! (atomics, a lock, or a cell with its own access rules). A task can be
+//! preempted and migrated right after looking its slot up; use
+//! [`PerCpu::with`] when the work has to finish on the same CPU.
+//!
+//! On x86_64 both `GS_BASE` and `KERNEL_GS_BASE` hold the area, so GS stays
+//! valid across the `swapgs` in the syscall path however it is balanced,
+//! and in interrupts taken from user mode. Interrupt handlers swap GS back
+//! in if user code loaded its own GS selector (see `arch::x86_64::idt`).
+//! The context switch code leaves `TPIDR_EL1` and `tp` alone for the same
+//! reason: they describe the CPU, not the task.
+//!
+//! Until the boot CPU has installed its area, [`cpu_id`] returns 0.
+//! Application processors must call [`init_cpu`] before anything else.
+
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::smp::MAX_CPUS;
+use crate::mm::cache_aligned::CacheAligned;
+
+/// The block each CPU's base register points at.
+#[repr(C, align(64))]
+pub struct CpuArea {
+    /// Kernel stack loaded by the x86_64 `syscall_entry` (offset 0x0).
+    pub kernel_rsp: u64,
+    /// User stack saved by the x86_64 `syscall_entry` (offset 0x8).
+    pub user_rsp: u64,
+    /// This CPU's number (offset 0x10).
+    cpu_id: u64,
+}
+
+impl CpuArea {
+    const fn new() -> Self {
+        Self {
+            kernel_rsp: 0,
+            user_rsp: 0,
+            cpu_id: 0,
+        }
+    }
+}
+
+/// Offset of [`CpuArea::cpu_id`], used by the register-relative load.
+const CPU_ID_OFFSET: usize = core::mem::offset_of!(CpuArea, cpu_id);
+
+struct AreaCell(UnsafeCell<CpuArea>);
+
+// SAFETY: A CPU writes its own area's cpu_id once in init_cpu() before
+// publishing the base register. The syscall stack fields are only touched
+// by their own CPU, from syscall entry/exit and the code that prepares it.
+unsafe impl Sync for AreaCell {}
+
+static AREAS: [AreaCell; MAX_CPUS] =
+    [const { AreaCell(UnsafeCell::new(CpuArea::new())) }; MAX_CPUS];
+
+/// Set once the boot CPU's base register points at its area.
+static BASE_READY: AtomicBool = AtomicBool::new(false);
+
+/// Point this CPU's base register at the area of `cpu`.
+///
+/// Called by the boot CPU before architecture initialization, and by each
+/// application processor as the first thing it does.
+pub fn init_cpu(cpu: usize) {
+    assert!(cpu < MAX_CPUS, "CPU number out of range");
+    let area = AREAS[cpu].0.get();
+    // SAFETY: Only this CPU initializes its area, and nothing reads the
+    // area through the base register until the register is written below.
+    unsafe { (*area).cpu_id = cpu as u64 };
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use x86_64::{
+            registers::model_specific::{GsBase, KernelGsBase},
+            VirtAddr,
+        };
+        let addr = VirtAddr::new(area as u64);
+        GsBase::write(addr);
+        KernelGsBase::write(addr);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: TPIDR_EL1 is reserved for the per-CPU area; no other code
+    // reads or writes it.
+    unsafe {
+        core::arch::asm!("msr TPIDR_EL1, {}", in(reg) area, options(nomem, nostack));
+    }
+
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    // SAFETY: The kernel does not use thread-local storage, so tp is free
+    // to hold the per-CPU area while running in supervisor mode.
+    unsafe {
+        core::arch::asm!("mv tp, {}", in(reg) area, options(nomem, nostack));
+    }
+
+    BASE_READY.store(true, Ordering::Release);
+}
+
+/// Number of the CPU the caller is running on.
+#[inline]
+pub fn cpu_id() -> usize {
+    if !BASE_READY.load(Ordering::Relaxed) {
+        return 0;
+    }
+    // SAFETY: Once BASE_READY is set the boot CPU's base register holds its
+    // area, and every other CPU installs its own before running kernel code.
+    unsafe { read_cpu_id() }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn read_cpu_id() -> usize {
+    let id: usize;
+    // SAFETY: GS points at this CPU's CpuArea (see the module docs).
+    unsafe {
+        core::arch::asm!(
+            "mov {}, gs:[{off}]",
+            out(reg) id,
+            off = const CPU_ID_OFFSET,
+            options(nostack, preserves_flags, readonly),
+        );
+    }
+    id
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+unsafe fn read_cpu_id() -> usize {
+    let area: *const CpuArea;
+    // SAFETY: TPIDR_EL1 holds this CPU's CpuArea.
+    unsafe {
+        core::arch::asm!("mrs {}, TPIDR_EL1", out(reg) area, options(nomem, nostack));
+        (*area).cpu_id as usize
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[inline(always)]
+unsafe fn read_cpu_id() -> usize {
+    let area: *const CpuArea;
+    // SAFETY: tp holds this CPU's CpuArea.
+    unsafe {
+        core::arch::asm!("mv {}, tp", out(reg) area, options(nomem, nostack));
+        (*area).cpu_id as usize
+    }
+}
+
+/// Raw pointer to the current CPU's area.
+///
+/// The syscall code uses it to set up the stacks `syscall_entry` switches
+/// between.
+pub fn this_area() -> *mut CpuArea {
+    AREAS[cpu_id()].0.get()
+}
+
+/// A variable with one instance per CPU. Declare with
+/// [`define_per_cpu!`](crate::define_per_cpu).
+pub struct PerCpu<T> {
+    slots: [CacheAligned<T>; MAX_CPUS],
+}
+
+impl<T> PerCpu<T> {
+    #[doc(hidden)]
+    pub const fn from_slots(slots: [CacheAligned<T>; MAX_CPUS]) -> Self {
+        Self { slots }
+    }
+
+    /// The slot of the CPU the caller is running on.
+    #[inline]
+    pub fn this_cpu(&self) -> &T {
+        &self.slots[cpu_id()]
+    }
+
+    /// The slot of `cpu`. Panics if `cpu >= MAX_CPUS`.
+    #[inline]
+    pub fn on_cpu(&self, cpu: usize) -> &T {
+        &self.slots[cpu]
+    }
+
+    /// Run `f` on this CPU's slot with interrupts masked, so the task
+    /// cannot migrate and nothing else on this CPU runs until it returns.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let _irq = crate::arch::disable_interrupts();
+        f(self.this_cpu())
+    }
+
+    /// Every CPU's slot, in CPU order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().map(|slot| &**slot)
+    }
+}
+
+/// Declare per-CPU statics.
+///
+/// ```rust,ignore
+/// define_per_cpu! {
+///     /// Events seen by each CPU.
+///     static EVENTS: AtomicU64 = AtomicU64::new(0);
+/// }
+///
+/// EVENTS.this_cpu().fetch_add(1, Ordering::Relaxed);
+/// let total: u64 = EVENTS.iter().map(|n| n.load(Ordering::Relaxed)).sum();
+/// ```
+///
+/// The initializer must be a constant expression; it is evaluated once per
+/// CPU.
+#[macro_export]
+macro_rules! define_per_cpu {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident: $ty:ty = $init:expr;
+    )*) => {$(
+        $(#[$attr])*
+        $vis static $name: $crate::sched::percpu::PerCpu<$ty> =
+            $crate::sched::percpu::PerCpu::from_slots(
+                [const { $crate::mm::cache_aligned::CacheAligned::new($init) };
+                    $crate::sched::smp::MAX_CPUS],
+            );
+    )*};
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    crate::define_per_cpu! {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+    }
+
+    #[test]
+    fn test_area_layout() {
+        // syscall_entry uses gs:[0x0] and gs:[0x8].
+        assert_eq!(core::mem::offset_of!(CpuArea, kernel_rsp), 0x0);
+        assert_eq!(core::mem::offset_of!(CpuArea, user_rsp), 0x8);
+        assert_eq!(CPU_ID_OFFSET, 0x10);
+    }
+
+    #[test]
+    fn test_this_cpu_matches_cpu_id() {
+        let cpu = cpu_id();
+        assert!(cpu < MAX_CPUS);
+        assert!(core::ptr::eq(COUNTER.this_cpu(), COUNTER.on_cpu(cpu)));
+        assert_eq!(this_area(), AREAS[cpu].0.get());
+    }
+
+    #[test]
+    fn test_slots_are_independent() {
+        let cpu = cpu_id();
+        let other = (cpu + 1) % MAX_CPUS;
+        let before = COUNTER.on_cpu(other).load(Ordering::Relaxed);
+        COUNTER.with(|n| n.fetch_add(3, Ordering::Relaxed));
+        assert_eq!(COUNTER.on_cpu(other).load(Ordering::Relaxed), before);
+        assert_eq!(COUNTER.iter().count(), MAX_CPUS);
+        let total: u32 = COUNTER.iter().map(|n| n.load(Ordering::Relaxed)).sum();
+        assert!(total >= 3);
+    }
+}
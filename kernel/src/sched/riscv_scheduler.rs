@@ -26,6 +26,11 @@ impl RiscvScheduler {
     pub fn lock(&self) -> spin::MutexGuard<'_, Scheduler> {
         self.inner.lock()
     }
+
+    /// Try to acquire the scheduler lock without spinning.
+    pub fn try_lock(&self) -> Option<spin::MutexGuard<'_, Scheduler>> {
+        self.inner.try_lock()
+    }
 }
 
 impl Default for RiscvScheduler {
@@ -12,40 +12,17 @@ use super::scheduler;
 pub fn start() -> ! {
     kprintln!("[SCHED] Starting scheduler execution");
 
-    #[cfg(target_arch = "aarch64")]
-    {
-        // Enter idle loop
-        loop {
-            // SAFETY: WFI (Wait For Interrupt) is a hint instruction that
-            // puts the CPU into a low-power state until an interrupt arrives.
-            // It does not modify any memory or registers beyond the PC. The
-            // nomem/nostack/preserves_flags options correctly reflect this.
-            unsafe {
-                core::arch::asm!("wfi", options(nomem, nostack, preserves_flags));
-            }
-        }
-    }
-
-    #[cfg(target_arch = "riscv64")]
-    {
-        // Enter idle loop
-        loop {
-            // SAFETY: WFI (Wait For Interrupt) is a hint instruction on RISC-V
-            // that suspends execution until an interrupt occurs. It does not
-            // modify memory or registers. The options correctly reflect this.
-            unsafe {
-                core::arch::asm!("wfi", options(nomem, nostack, preserves_flags));
-            }
-        }
-    }
-
-    // x86_64: Enter HLT idle loop (matches AArch64 WFI and RISC-V WFI)
     #[cfg(target_arch = "x86_64")]
-    {
-        println!("[SCHED] Entering idle loop");
-        loop {
-            crate::arch::idle();
-        }
+    println!("[SCHED] Entering idle loop");
+
+    // Enter idle loop (cpuidle picks HLT/MWAIT, WFE or WFI)
+    loop {
+        super::workqueue::run_pending();
+        #[cfg(feature = "alloc")]
+        crate::services::devmgr::poll();
+        #[cfg(feature = "net")]
+        crate::net::poll();
+        crate::power::cpuidle::idle();
     }
 }
 
@@ -96,8 +73,19 @@ pub fn run() -> ! {
             }
         }
 
+        // Run deferred work no kworker has picked up yet
+        super::workqueue::run_pending();
+
+        // Restart crashed drivers
+        #[cfg(feature = "alloc")]
+        crate::services::devmgr::poll();
+
+        // Hand received network frames to the stack
+        #[cfg(feature = "net")]
+        crate::net::poll();
+
         // Enter low power state
-        crate::arch::idle();
+        crate::power::cpuidle::idle();
     }
 }
 
@@ -108,7 +96,14 @@ pub extern "C" fn idle_task_entry() -> ! {
 
 /// Handle timer tick
 pub fn timer_tick() {
+    crate::drivers::watchdog::check();
     scheduler::current_scheduler().lock().tick();
+    crate::sync::rcu::rcu_quiescent();
+    super::workqueue::tick();
+    crate::power::timer_tick();
+    crate::graphics::vsync_sw::vblank_tick();
+    crate::audio::pipeline::pump();
+    crate::drivers::serial::poll();
 }
 
 /// Set scheduling algorithm
@@ -525,6 +525,17 @@ impl Scheduler {
 
     /// Handle timer tick
     pub fn tick(&mut self) {
+        crate::drivers::watchdog::sched_heartbeat();
+
+        // Lets the vDSO's sched_yield() skip the trap when nothing else can
+        // run. Without per-CPU data tasks sit in the global queue, which is
+        // not counted, so make every yield trap.
+        let ready_tasks = match super::smp::per_cpu(self.cpu_id) {
+            Some(_) => super::smp::ready_task_count() as u64,
+            None => u64::MAX,
+        };
+        crate::process::vdso::publish_ready_tasks(ready_tasks);
+
         if let Some(current) = self.current {
             // SAFETY: `current` is a TaskPtr stored in the scheduler which
             // points to a valid Task. We are called from the timer interrupt
@@ -538,8 +549,12 @@ impl Scheduler {
                     (*task_mut).time_slice -= 1;
                 }
 
-                // Check if time slice expired
-                if (*task_mut).time_slice == 0 && self.is_preemptible() {
+                // Check if time slice expired. A task inside an RCU
+                // read-side critical section keeps the CPU until it leaves.
+                if (*task_mut).time_slice == 0
+                    && self.is_preemptible()
+                    && !crate::sync::rcu::rcu_is_reading()
+                {
                     (*task_mut).time_slice = DEFAULT_TIME_SLICE;
                     self.schedule();
                 }
@@ -550,6 +565,7 @@ impl Scheduler {
     /// Perform scheduling decision
     pub fn schedule(&mut self) {
         let start_cycles = super::metrics::read_tsc();
+        crate::drivers::watchdog::sched_heartbeat();
 
         // Disable interrupts
         let _guard = crate::arch::disable_interrupts();
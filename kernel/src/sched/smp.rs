@@ -43,7 +43,8 @@ pub struct CpuInfo {
     pub model: String,
     /// CPU features
     pub features: CpuFeatures,
-    /// Per-CPU page frame cache index (matches PER_CPU_PAGE_CACHES slot)
+    /// Per-CPU page frame cache index (matches the frame allocator's
+    /// PAGE_CACHES slot)
     pub page_cache_id: u8,
 }
 
@@ -209,6 +210,16 @@ impl CpuTopology {
                 self.total_cpus = logical_cpus;
             }
         }
+
+        // CPUID only describes the package this CPU sits in; the MADT lists
+        // every processor the firmware brought up.
+        if let Some(cpus) = crate::arch::x86_64::acpi::with_acpi_info(|info| {
+            info.has_madt.then(|| info.cpu_count())
+        })
+        .flatten()
+        {
+            self.total_cpus = (cpus as u8).max(1);
+        }
     }
 
     #[cfg(target_arch = "aarch64")]
@@ -299,9 +310,17 @@ static CPU_TOPOLOGY: Mutex<CpuTopology> = Mutex::new(CpuTopology {
     numa_nodes: Vec::new(),
 });
 
+crate::kernel_param! {
+    /// Keep application processors offline; run on the bootstrap processor only.
+    pub static NOSMP: bool = false, "nosmp";
+}
+
 /// Initialize SMP support
 pub fn init() {
     kprintln!("[SMP] Initializing SMP support (BSP only)...");
+    if NOSMP.get() {
+        kprintln!("[SMP] nosmp: application processors stay offline");
+    }
 
     // All architectures currently use simplified BSP-only initialization.
     // Complex topology detection and AP wakeup deferred to Phase 3+.
@@ -391,43 +410,11 @@ pub fn per_cpu(cpu_id: u8) -> Option<&'static PerCpuData> {
 }
 
 /// Get current CPU ID
+///
+/// Read through the per-CPU base register; see [`super::percpu`].
+#[inline]
 pub fn current_cpu_id() -> u8 {
-    #[cfg(target_arch = "x86_64")]
-    {
-        // SAFETY: CPUID leaf 0x1 is an unprivileged read-only instruction.
-        // The initial APIC ID is in bits 31:24 of EBX. This is safe to call
-        // at any time on x86_64.
-        unsafe {
-            use core::arch::x86_64::__cpuid;
-            let cpuid = __cpuid(0x1);
-            ((cpuid.ebx >> 24) & 0xFF) as u8
-        }
-    }
-
-    #[cfg(target_arch = "aarch64")]
-    {
-        // SAFETY: MPIDR_EL1 is a read-only system register accessible from
-        // EL1 (kernel mode). Bits [7:0] (Aff0) contain the CPU thread ID
-        // within the core. Reading has no side effects.
-        unsafe {
-            let mpidr: u64;
-            core::arch::asm!("mrs {}, MPIDR_EL1", out(reg) mpidr);
-            (mpidr & 0xFF) as u8
-        }
-    }
-
-    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
-    {
-        // SAFETY: mhartid is a read-only CSR that returns the hardware
-        // thread (hart) ID. It is always readable from M-mode. This may
-        // trap in S-mode if not delegated, but during bootstrap we run in
-        // M-mode or the SBI provides this value.
-        unsafe {
-            let hartid: usize;
-            core::arch::asm!("csrr {}, mhartid", out(reg) hartid);
-            hartid as u8
-        }
-    }
+    super::percpu::cpu_id() as u8
 }
 
 /// Send inter-processor interrupt
@@ -509,6 +496,11 @@ pub fn cpu_up(cpu_id: u8) -> Result<(), KernelError> {
             value: "exceeds MAX_CPUS",
         });
     }
+    if cpu_id != 0 && NOSMP.get() {
+        return Err(KernelError::OperationNotSupported {
+            operation: "CPU bring-up with nosmp",
+        });
+    }
 
     if let Some(cpu_data) = per_cpu(cpu_id) {
         if cpu_data.cpu_info.is_online() {
@@ -751,6 +743,15 @@ pub fn migrate_task(
     }
 }
 
+/// Number of tasks waiting in the ready queues of all online CPUs
+pub fn ready_task_count() -> u32 {
+    (0..MAX_CPUS as u8)
+        .filter_map(per_cpu)
+        .filter(|cpu_data| cpu_data.cpu_info.is_online())
+        .map(|cpu_data| cpu_data.cpu_info.nr_running.load(Ordering::Relaxed))
+        .sum()
+}
+
 /// Find least loaded CPU
 pub fn find_least_loaded_cpu() -> u8 {
     let mut min_load = 100;
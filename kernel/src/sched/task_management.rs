@@ -165,7 +165,7 @@ pub fn exit_task(exit_code: i32) {
 
     // Should not return
     loop {
-        crate::arch::idle();
+        crate::power::cpuidle::idle();
     }
 }
 
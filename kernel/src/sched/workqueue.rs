@@ -0,0 +1,513 @@
+//! Deferred work (workqueues)
+//!
+//! Interrupt handlers must return quickly and may not block. Anything
+//! heavier is described by a static [`Work`] item which the handler queues
+//! with [`queue_work`]; the item's function then runs in process context on
+//! the same CPU, in that CPU's `kworker/N` task or in its idle loop,
+//! whichever gets there first.
+//!
+//! An item is queued at most once at a time: queueing it again while it is
+//! pending does nothing, so its function runs once however many times it
+//! was queued before it started. Queueing it while it runs makes it run
+//! again afterwards, on the CPU that is running it, so an item never runs
+//! on two CPUs at once. [`queue_delayed_work`] holds the item back for a
+//! number of milliseconds; [`flush_work`] and [`flush`] wait for queued
+//! work to finish.
+//!
+//! Work items are `&'static` and linked into the queues through their own
+//! fields, so queueing never allocates and is safe from interrupt context.
+//!
+//! ```ignore
+//! static RX_WORK: Work = Work::new(rx_work, 0);
+//!
+//! fn rx_work(_data: usize) {
+//!     // hand received frames to the stack
+//! }
+//!
+//! fn nic_interrupt() {
+//!     // acknowledge the device, then
+//!     workqueue::queue_work(&RX_WORK);
+//! }
+//! ```
+
+use core::{
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+};
+
+use spin::Mutex;
+
+use super::{smp, task::Task};
+use crate::process::ProcessState;
+
+/// Function run for a work item; receives the item's data word.
+pub type WorkFn = fn(usize);
+
+/// The item is on a CPU's ready or delayed list.
+const PENDING: u8 = 1 << 0;
+/// The item's function is executing.
+const RUNNING: u8 = 1 << 1;
+
+/// A unit of deferred work.
+pub struct Work {
+    func: WorkFn,
+    data: AtomicUsize,
+    state: AtomicU8,
+    /// CPU whose lists hold the item while it is pending, or that last ran
+    /// it.
+    cpu: AtomicU8,
+    /// Timestamp (ms) at which delayed work becomes ready.
+    expires_ms: AtomicU64,
+    /// Next item on the same list. Only accessed under that CPU's queue
+    /// lock.
+    next: AtomicPtr<Work>,
+}
+
+impl Work {
+    /// Create a work item that calls `func(data)`.
+    pub const fn new(func: WorkFn, data: usize) -> Self {
+        Self {
+            func,
+            data: AtomicUsize::new(data),
+            state: AtomicU8::new(0),
+            cpu: AtomicU8::new(0),
+            expires_ms: AtomicU64::new(0),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Change the word passed to the function on its next run.
+    pub fn set_data(&self, data: usize) {
+        self.data.store(data, Ordering::Release);
+    }
+
+    /// Whether the item is queued and has not started yet.
+    pub fn is_pending(&self) -> bool {
+        self.state.load(Ordering::Acquire) & PENDING != 0
+    }
+
+    /// Whether the item is queued or running.
+    pub fn is_busy(&self) -> bool {
+        self.state.load(Ordering::Acquire) != 0
+    }
+}
+
+/// Singly linked list threaded through [`Work::next`].
+struct WorkList {
+    head: Option<&'static Work>,
+    tail: Option<&'static Work>,
+}
+
+impl WorkList {
+    const fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn push(&mut self, work: &'static Work) {
+        work.next.store(ptr::null_mut(), Ordering::Relaxed);
+        match self.tail {
+            Some(tail) => tail.next.store(as_link(work), Ordering::Relaxed),
+            None => self.head = Some(work),
+        }
+        self.tail = Some(work);
+    }
+
+    fn pop(&mut self) -> Option<&'static Work> {
+        let work = self.head?;
+        self.head = next_of(work);
+        if self.head.is_none() {
+            self.tail = None;
+        }
+        Some(work)
+    }
+
+    fn remove(&mut self, work: &'static Work) -> bool {
+        let mut prev: Option<&'static Work> = None;
+        let mut cur = self.head;
+        while let Some(item) = cur {
+            let next = next_of(item);
+            if ptr::eq(item, work) {
+                match prev {
+                    Some(p) => p
+                        .next
+                        .store(item.next.load(Ordering::Relaxed), Ordering::Relaxed),
+                    None => self.head = next,
+                }
+                if self.tail.is_some_and(|t| ptr::eq(t, work)) {
+                    self.tail = prev;
+                }
+                return true;
+            }
+            prev = cur;
+            cur = next;
+        }
+        false
+    }
+}
+
+fn as_link(work: &'static Work) -> *mut Work {
+    work as *const Work as *mut Work
+}
+
+fn next_of(work: &Work) -> Option<&'static Work> {
+    // SAFETY: links are only ever set from `&'static Work` in `push`.
+    unsafe { work.next.load(Ordering::Relaxed).as_ref() }
+}
+
+/// Work queued on one CPU.
+struct Lists {
+    ready: WorkList,
+    /// Delayed work, in no particular order.
+    delayed: WorkList,
+}
+
+struct CpuQueue {
+    lists: Mutex<Lists>,
+    /// Set while a runner is executing this CPU's work.
+    busy: AtomicBool,
+    /// The CPU's kworker task, once started.
+    worker: AtomicPtr<Task>,
+    /// The kworker is blocked waiting for work.
+    sleeping: AtomicBool,
+}
+
+impl CpuQueue {
+    const fn new() -> Self {
+        Self {
+            lists: Mutex::new(Lists {
+                ready: WorkList::new(),
+                delayed: WorkList::new(),
+            }),
+            busy: AtomicBool::new(false),
+            worker: AtomicPtr::new(ptr::null_mut()),
+            sleeping: AtomicBool::new(false),
+        }
+    }
+
+    /// Run `f` on the lists with interrupts masked, so that interrupt
+    /// handlers on this CPU can queue work while process context holds the
+    /// lock.
+    fn with<R>(&self, f: impl FnOnce(&mut Lists) -> R) -> R {
+        let _irq = crate::arch::disable_interrupts();
+        f(&mut self.lists.lock())
+    }
+}
+
+crate::define_per_cpu! {
+    static QUEUES: CpuQueue = CpuQueue::new();
+}
+
+fn queue(cpu: u8) -> &'static CpuQueue {
+    QUEUES.on_cpu(cpu as usize % smp::MAX_CPUS)
+}
+
+fn now_ms() -> u64 {
+    crate::arch::timer::get_timestamp_ms()
+}
+
+/// Queue `work` on the current CPU.
+///
+/// Returns `false` if it was already pending.
+pub fn queue_work(work: &'static Work) -> bool {
+    queue_work_on(smp::current_cpu_id(), work)
+}
+
+/// Queue `work` on `cpu`.
+///
+/// Returns `false` if it was already pending.
+pub fn queue_work_on(cpu: u8, work: &'static Work) -> bool {
+    enqueue(cpu, work, None)
+}
+
+/// Queue `work` on the current CPU once `delay_ms` milliseconds have
+/// passed. Delays are checked on each timer tick.
+///
+/// Returns `false` if it was already pending; the existing delay is kept.
+pub fn queue_delayed_work(work: &'static Work, delay_ms: u64) -> bool {
+    let expires = if delay_ms == 0 {
+        None
+    } else {
+        Some(now_ms().saturating_add(delay_ms))
+    };
+    enqueue(smp::current_cpu_id(), work, expires)
+}
+
+fn enqueue(cpu: u8, work: &'static Work, expires_ms: Option<u64>) -> bool {
+    let prev = work.state.fetch_or(PENDING, Ordering::AcqRel);
+    if prev & PENDING != 0 {
+        return false;
+    }
+    // Work that is running stays on its CPU.
+    let cpu = if prev & RUNNING != 0 {
+        work.cpu.load(Ordering::Acquire)
+    } else {
+        cpu
+    };
+    work.cpu.store(cpu, Ordering::Release);
+    queue(cpu).with(|lists| match expires_ms {
+        Some(expires) => {
+            work.expires_ms.store(expires, Ordering::Relaxed);
+            lists.delayed.push(work);
+        }
+        None => lists.ready.push(work),
+    });
+    true
+}
+
+/// Take `work` off its queue if it has not started.
+///
+/// Returns `true` if it was pending. Does not wait for a run in progress;
+/// use [`flush_work`] afterwards for that.
+pub fn cancel_work(work: &'static Work) -> bool {
+    if !work.is_pending() {
+        return false;
+    }
+    queue(work.cpu.load(Ordering::Acquire)).with(|lists| {
+        let removed = lists.ready.remove(work) || lists.delayed.remove(work);
+        if removed {
+            work.state.fetch_and(!PENDING, Ordering::AcqRel);
+        }
+        removed
+    })
+}
+
+/// Run the work queued on the current CPU until its ready list is empty.
+///
+/// Called by the kworkers and the idle loop; must not be called from
+/// interrupt context. Returns at once if another task on this CPU is
+/// already running work, since it was preempted and will carry on.
+pub fn run_pending() {
+    let q = queue(smp::current_cpu_id());
+    if q.busy.swap(true, Ordering::Acquire) {
+        return;
+    }
+    while let Some(work) = q.with(|lists| {
+        let work = lists.ready.pop()?;
+        work.state.fetch_or(RUNNING, Ordering::AcqRel);
+        work.state.fetch_and(!PENDING, Ordering::AcqRel);
+        Some(work)
+    }) {
+        (work.func)(work.data.load(Ordering::Acquire));
+        work.state.fetch_and(!RUNNING, Ordering::Release);
+    }
+    q.busy.store(false, Ordering::Release);
+}
+
+/// Wait until `work` is neither pending nor running. Delayed work is made
+/// ready at once.
+///
+/// Must not be called from interrupt context, nor from a work function for
+/// an item queued on the same CPU.
+pub fn flush_work(work: &'static Work) {
+    let cpu = work.cpu.load(Ordering::Acquire);
+    queue(cpu).with(|lists| {
+        if lists.delayed.remove(work) {
+            lists.ready.push(work);
+        }
+    });
+    while work.is_busy() {
+        if work.cpu.load(Ordering::Acquire) == smp::current_cpu_id() {
+            run_pending();
+        }
+        if work.is_busy() {
+            super::yield_cpu();
+        }
+    }
+}
+
+/// Wait until every CPU's ready list is empty and no work is running.
+/// Delayed work that has not expired is left queued.
+///
+/// Same restrictions as [`flush_work`].
+pub fn flush() {
+    run_pending();
+    while QUEUES
+        .iter()
+        .any(|q| q.busy.load(Ordering::Acquire) || !q.with(|lists| lists.ready.is_empty()))
+    {
+        super::yield_cpu();
+        run_pending();
+    }
+}
+
+/// Timer tick: make expired delayed work ready and wake this CPU's kworker
+/// if it has work. Called from interrupt context.
+pub fn tick() {
+    let cpu = smp::current_cpu_id();
+    let q = queue(cpu);
+    let has_work = q.with(|lists| {
+        if !lists.delayed.is_empty() {
+            let now = now_ms();
+            let mut waiting = WorkList::new();
+            while let Some(work) = lists.delayed.pop() {
+                if work.expires_ms.load(Ordering::Relaxed) <= now {
+                    lists.ready.push(work);
+                } else {
+                    waiting.push(work);
+                }
+            }
+            lists.delayed = waiting;
+        }
+        !lists.ready.is_empty()
+    });
+    if has_work && q.sleeping.swap(false, Ordering::AcqRel) {
+        if let Some(task) = NonNull::new(q.worker.load(Ordering::Acquire)) {
+            // SAFETY: kworker tasks are leaked at creation and never freed.
+            // It is blocked, so nothing else is touching its state.
+            unsafe { (*task.as_ptr()).state = ProcessState::Ready };
+            super::scheduler::schedule_on_cpu(cpu, task);
+        }
+    }
+}
+
+/// kworker main loop: run this CPU's work, then sleep until [`tick`] finds
+/// more.
+extern "C" fn kworker_entry() -> ! {
+    let q = queue(smp::current_cpu_id());
+    // SAFETY: the worker pointer is set before the task is first scheduled
+    // and the task is never freed.
+    let pid = unsafe { (*q.worker.load(Ordering::Acquire)).pid };
+    loop {
+        run_pending();
+        // With interrupts masked, no tick can slip in between the check and
+        // blocking, so a wakeup is never missed.
+        let _irq = crate::arch::disable_interrupts();
+        if q.lists.lock().ready.is_empty() {
+            q.sleeping.store(true, Ordering::Release);
+            super::block_process(pid);
+        }
+    }
+}
+
+/// Start a `kworker/N` task on `cpu`.
+#[cfg(feature = "alloc")]
+fn spawn_kworker(cpu: u8) {
+    use alloc::{boxed::Box, format, vec};
+
+    use super::{
+        process_compat::alloc_pid,
+        task::{alloc_tid, CpuSet, Priority},
+    };
+
+    const KWORKER_STACK_SIZE: usize = 16 * 1024;
+    let stack = Box::leak(vec![0u8; KWORKER_STACK_SIZE].into_boxed_slice());
+    let stack_top = (stack.as_ptr() as usize + KWORKER_STACK_SIZE) & !0xF;
+
+    let pid = alloc_pid();
+    let mut task = Box::new(Task::new(
+        pid,
+        alloc_tid(),
+        format!("kworker/{}", cpu),
+        kworker_entry as usize,
+        stack_top,
+        crate::mm::get_kernel_page_table(),
+    ));
+    task.priority = Priority::SystemNormal;
+    task.cpu_affinity = CpuSet::single(cpu);
+
+    // Box::leak always returns a non-null pointer
+    let task_ptr =
+        NonNull::new(Box::leak(task) as *mut _).expect("Box::leak returned null (impossible)");
+    queue(cpu)
+        .worker
+        .store(task_ptr.as_ptr(), Ordering::Release);
+    super::scheduler::register_task(pid.0, task_ptr);
+    super::scheduler::schedule_on_cpu(cpu, task_ptr);
+}
+
+/// Start a kworker on each online CPU.
+///
+/// Work queued before this, or on a CPU without a kworker, is still run by
+/// that CPU's idle loop.
+#[cfg(feature = "alloc")]
+pub fn init() {
+    for cpu in 0..smp::MAX_CPUS as u8 {
+        let online = smp::per_cpu(cpu).is_some_and(|data| data.cpu_info.is_online());
+        if online && queue(cpu).worker.load(Ordering::Acquire).is_null() {
+            spawn_kworker(cpu);
+        }
+    }
+    kprintln!("[SCHED] Workqueue kworkers started");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_list() {
+        static A: Work = Work::new(|_| {}, 0);
+        static B: Work = Work::new(|_| {}, 0);
+        static C: Work = Work::new(|_| {}, 0);
+
+        let mut list = WorkList::new();
+        list.push(&A);
+        list.push(&B);
+        list.push(&C);
+        assert!(list.remove(&C));
+        assert!(!list.remove(&C));
+        list.push(&C);
+        assert!(list.remove(&B));
+        assert!(ptr::eq(list.pop().unwrap(), &A));
+        assert!(ptr::eq(list.pop().unwrap(), &C));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_queue_runs_once() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        static WORK: Work = Work::new(
+            |data| {
+                RUNS.fetch_add(data, Ordering::Relaxed);
+            },
+            1,
+        );
+
+        assert!(queue_work(&WORK));
+        assert!(!queue_work(&WORK));
+        assert!(WORK.is_pending());
+        run_pending();
+        assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+        assert!(!WORK.is_busy());
+    }
+
+    #[test]
+    fn test_cancel_work() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        static WORK: Work = Work::new(
+            |_| {
+                RUNS.fetch_add(1, Ordering::Relaxed);
+            },
+            0,
+        );
+
+        assert!(queue_delayed_work(&WORK, 60_000));
+        assert!(cancel_work(&WORK));
+        assert!(!cancel_work(&WORK));
+        run_pending();
+        assert_eq!(RUNS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_flush_runs_delayed_work() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        static WORK: Work = Work::new(
+            |_| {
+                RUNS.fetch_add(1, Ordering::Relaxed);
+            },
+            0,
+        );
+
+        assert!(queue_delayed_work(&WORK, 60_000));
+        flush_work(&WORK);
+        assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+        assert!(!WORK.is_busy());
+    }
+}
@@ -259,6 +259,116 @@ fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32) -> Hash256
     Hash256(result)
 }
 
+// ---------------------------------------------------------------------------
+// Password Hash Strings (crypt)
+// ---------------------------------------------------------------------------
+
+/// Identifier of PBKDF2-HMAC-SHA256 hash strings.
+pub const CRYPT_PREFIX: &str = "$pbkdf2-sha256$";
+
+/// Longest string produced by [`crypt`]: prefix, rounds, salt and digest.
+pub const CRYPT_MAX_LEN: usize = 160;
+
+/// Longest accepted salt, in characters.
+const CRYPT_MAX_SALT: usize = 64;
+
+/// Upper bound on rounds. Hashing runs in the syscall without preemption,
+/// so any user could otherwise hold a CPU for as long as they liked; this
+/// allows ten times the 10,000 rounds the kernel uses itself.
+const CRYPT_MAX_ROUNDS: u32 = 100_000;
+
+/// Hash `key` as described by `setting`, in the style of `crypt(3)`.
+///
+/// `setting` is `$pbkdf2-sha256$<rounds>$<salt>` optionally followed by
+/// `$<digest>`, so a stored hash string can be passed back in to verify a
+/// password. Salt characters are limited to `[A-Za-z0-9./]`. The complete
+/// hash string `$pbkdf2-sha256$<rounds>$<salt>$<hex digest>` is written to
+/// `out` (which should hold [`CRYPT_MAX_LEN`] bytes) and its length is
+/// returned.
+pub fn crypt(key: &[u8], setting: &str, out: &mut [u8]) -> Result<usize, KernelError> {
+    let invalid = |value| KernelError::InvalidArgument {
+        name: "setting",
+        value,
+    };
+
+    let rest = setting
+        .strip_prefix(CRYPT_PREFIX)
+        .ok_or(invalid("unsupported hash method"))?;
+    let mut fields = rest.split('$');
+    let rounds_str = fields.next().unwrap_or("");
+    let salt = fields.next().ok_or(invalid("missing salt"))?;
+
+    let rounds: u32 = rounds_str.parse().map_err(|_| invalid("bad rounds"))?;
+    if rounds == 0 || rounds > CRYPT_MAX_ROUNDS {
+        return Err(invalid("rounds out of range"));
+    }
+    if salt.is_empty()
+        || salt.len() > CRYPT_MAX_SALT
+        || !salt
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'/')
+    {
+        return Err(invalid("bad salt"));
+    }
+
+    let digest = pbkdf2_hmac_sha256(key, salt.as_bytes(), rounds);
+
+    // Assemble into `out` without touching the heap
+    let mut rounds_buf = [0u8; 10];
+    let rounds_text = format_decimal(rounds, &mut rounds_buf);
+    let total = CRYPT_PREFIX.len() + rounds_text.len() + 1 + salt.len() + 1 + 64;
+    if out.len() < total {
+        return Err(KernelError::InvalidArgument {
+            name: "out",
+            value: "buffer too small",
+        });
+    }
+
+    let parts: [&[u8]; 5] = [
+        CRYPT_PREFIX.as_bytes(),
+        rounds_text,
+        b"$",
+        salt.as_bytes(),
+        b"$",
+    ];
+    let mut pos = 0;
+    for part in parts {
+        out[pos..pos + part.len()].copy_from_slice(part);
+        pos += part.len();
+    }
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    for byte in digest.as_bytes() {
+        out[pos] = HEX[(byte >> 4) as usize];
+        out[pos + 1] = HEX[(byte & 0xf) as usize];
+        pos += 2;
+    }
+
+    Ok(pos)
+}
+
+/// Check `key` against a stored `crypt` hash string in constant time.
+pub fn verify_crypt(key: &[u8], stored: &str) -> bool {
+    let mut buf = [0u8; CRYPT_MAX_LEN];
+    match crypt(key, stored, &mut buf) {
+        Ok(len) => crate::crypto::constant_time::ct_eq_bytes(&buf[..len], stored.as_bytes()) == 1,
+        Err(_) => false,
+    }
+}
+
+/// Render `value` in decimal into `buf`, returning the used tail.
+fn format_decimal(mut value: u32, buf: &mut [u8; 10]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    &buf[start..]
+}
+
 // ---------------------------------------------------------------------------
 // User Credential (legacy compat)
 // ---------------------------------------------------------------------------
@@ -1058,4 +1168,27 @@ mod tests {
         assert!(account.verify_password("newpassword"));
         assert!(!account.verify_password("original"));
     }
+
+    #[test]
+    fn test_crypt_round_trip() {
+        let mut buf = [0u8; CRYPT_MAX_LEN];
+        let len = crypt(b"hunter2", "$pbkdf2-sha256$50$abc./XYZ", &mut buf).unwrap();
+        let stored = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(stored.starts_with("$pbkdf2-sha256$50$abc./XYZ$"));
+        assert_eq!(len, "$pbkdf2-sha256$50$abc./XYZ$".len() + 64);
+
+        assert!(verify_crypt(b"hunter2", stored));
+        assert!(!verify_crypt(b"hunter3", stored));
+    }
+
+    #[test]
+    fn test_crypt_rejects_bad_settings() {
+        let mut buf = [0u8; CRYPT_MAX_LEN];
+        assert!(crypt(b"pw", "$6$salt$", &mut buf).is_err());
+        assert!(crypt(b"pw", "$pbkdf2-sha256$0$salt", &mut buf).is_err());
+        assert!(crypt(b"pw", "$pbkdf2-sha256$100001$salt", &mut buf).is_err());
+        assert!(crypt(b"pw", "$pbkdf2-sha256$10$sa:lt", &mut buf).is_err());
+        assert!(crypt(b"pw", "$pbkdf2-sha256$10", &mut buf).is_err());
+        assert!(!verify_crypt(b"", "*"));
+    }
 }
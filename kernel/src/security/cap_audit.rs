@@ -0,0 +1,339 @@
+//! Capability inspection and denial audit log
+//!
+//! Two tools for debugging and hardening the capability policy:
+//!
+//! - **Inspection**: enumerate the capabilities held by a process, either as
+//!   fixed-size records for `SYS_CAP_INSPECT` or as text for
+//!   `/proc/<pid>/caps`.
+//! - **Denial log**: every failed capability check is recorded in a fixed-size
+//!   kernel ring buffer with the subject PID, the capability token and object
+//!   type, the rights that were required, and the rights actually held. A
+//!   privileged audit daemon drains it with `SYS_AUDIT_READ`, using the
+//!   per-record sequence number to resume where it left off and to detect
+//!   overwritten records.
+//!
+//! Recording uses `try_lock()` and never allocates, so it is safe to call
+//! from the capability check fast path.
+
+use alloc::{format, string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spin::Mutex;
+
+use crate::cap::{CapabilitySpace, CapabilityToken, Rights};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Number of denial records kept in the ring buffer.
+pub const CAP_AUDIT_RING_SIZE: usize = 256;
+
+/// Object type reported when the capability was not found in the subject's
+/// capability space at all.
+pub const OBJECT_TYPE_NONE: u32 = 0xFF;
+
+// ---------------------------------------------------------------------------
+// Records
+// ---------------------------------------------------------------------------
+
+/// One capability held by a process (user-visible, `#[repr(C)]`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapInfoRecord {
+    /// Raw capability token.
+    pub token: u64,
+    /// Object type code (see [`object_type_name`]).
+    pub object_type: u32,
+    /// Rights bitmask (`Rights::bits()`).
+    pub rights: u32,
+}
+
+/// One denied capability check (user-visible, `#[repr(C)]`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapDenialRecord {
+    /// Monotonic sequence number, starting at 1.
+    pub seq: u64,
+    /// Timestamp (seconds since boot).
+    pub timestamp: u64,
+    /// PID of the process whose check failed (0 if unknown).
+    pub subject: u64,
+    /// Capability token presented.
+    pub cap: u64,
+    /// Object type of the capability, or [`OBJECT_TYPE_NONE`].
+    pub object_type: u32,
+    /// Rights the operation required.
+    pub required: u32,
+    /// Rights the subject actually held on the capability.
+    pub held: u32,
+    /// Reserved, always zero.
+    pub _reserved: u32,
+}
+
+/// Human-readable name for an `ObjectRef::type_code()` value.
+pub fn object_type_name(code: u32) -> &'static str {
+    match code {
+        0 => "memory",
+        1 => "process",
+        2 => "thread",
+        3 => "endpoint",
+        4 => "interrupt",
+        5 => "ioport",
+        6 => "pagetable",
+        7 => "capspace",
+        8 => "device",
+        OBJECT_TYPE_NONE => "none",
+        _ => "unknown",
+    }
+}
+
+/// Render a rights bitmask as a compact `rwxgrdmc` string.
+pub fn rights_string(rights: u32) -> String {
+    const LETTERS: [char; 8] = ['r', 'w', 'x', 'g', 'R', 'd', 'm', 'c'];
+    LETTERS
+        .iter()
+        .enumerate()
+        .map(|(bit, &c)| if rights & (1 << bit) != 0 { c } else { '-' })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Denial ring buffer
+// ---------------------------------------------------------------------------
+
+/// Fixed-size ring of denial records.
+struct DenialRing {
+    records: [CapDenialRecord; CAP_AUDIT_RING_SIZE],
+    /// Index of the next slot to write.
+    head: usize,
+    /// Number of valid records (saturates at the ring size).
+    len: usize,
+}
+
+impl DenialRing {
+    const fn new() -> Self {
+        Self {
+            records: [CapDenialRecord {
+                seq: 0,
+                timestamp: 0,
+                subject: 0,
+                cap: 0,
+                object_type: 0,
+                required: 0,
+                held: 0,
+                _reserved: 0,
+            }; CAP_AUDIT_RING_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, record: CapDenialRecord) {
+        self.records[self.head] = record;
+        self.head = (self.head + 1) % CAP_AUDIT_RING_SIZE;
+        if self.len < CAP_AUDIT_RING_SIZE {
+            self.len += 1;
+        }
+    }
+
+    /// Copy records with `seq >= since`, oldest first, into `out`.
+    fn read_since(&self, since: u64, out: &mut [CapDenialRecord]) -> usize {
+        let start = (self.head + CAP_AUDIT_RING_SIZE - self.len) % CAP_AUDIT_RING_SIZE;
+        let mut written = 0;
+        for i in 0..self.len {
+            if written == out.len() {
+                break;
+            }
+            let record = &self.records[(start + i) % CAP_AUDIT_RING_SIZE];
+            if record.seq >= since {
+                out[written] = *record;
+                written += 1;
+            }
+        }
+        written
+    }
+}
+
+static DENIALS: Mutex<DenialRing> = Mutex::new(DenialRing::new());
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// PID of the running task without blocking on the scheduler lock.
+fn current_subject() -> u64 {
+    match crate::sched::SCHEDULER.try_lock() {
+        Some(sched) => sched
+            .current()
+            // SAFETY: `current` is a valid NonNull<Task> owned by the
+            // scheduler, and we hold the scheduler lock while reading pid.
+            .map(|task| unsafe { task.as_ref().pid.0 })
+            .unwrap_or(0),
+        None => 0,
+    }
+}
+
+fn audit_timestamp() -> u64 {
+    #[cfg(target_os = "none")]
+    {
+        crate::arch::timer::get_timestamp_secs()
+    }
+    #[cfg(not(target_os = "none"))]
+    {
+        0
+    }
+}
+
+/// Record a denied capability check with explicit fields.
+pub fn record_denial(subject: u64, cap: u64, object_type: u32, required: u32, held: u32) {
+    let record = CapDenialRecord {
+        seq: NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+        timestamp: audit_timestamp(),
+        subject,
+        cap,
+        object_type,
+        required,
+        held,
+        _reserved: 0,
+    };
+    match DENIALS.try_lock() {
+        Some(mut ring) => ring.push(record),
+        // Graceful degradation: never block the capability check path
+        None => {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Record a failed `check_capability()` against `cap_space`.
+///
+/// Looks up what the subject actually holds so the record shows both the
+/// required and the held rights.
+pub fn record_check_failure(cap: CapabilityToken, required: Rights, cap_space: &CapabilitySpace) {
+    let (object_type, held) = match cap_space.lookup_entry(cap) {
+        Some((object, rights)) => (object.type_code() as u32, rights.bits() as u32),
+        None => (OBJECT_TYPE_NONE, 0),
+    };
+    record_denial(
+        current_subject(),
+        cap.to_u64(),
+        object_type,
+        required.bits() as u32,
+        held,
+    );
+}
+
+/// Copy denial records with sequence number `>= since` into `out`, oldest
+/// first. Returns the number of records written.
+pub fn read_denials(since: u64, out: &mut [CapDenialRecord]) -> usize {
+    DENIALS.lock().read_since(since, out)
+}
+
+/// Sequence number that the next recorded denial will receive.
+pub fn next_sequence() -> u64 {
+    NEXT_SEQ.load(Ordering::Relaxed)
+}
+
+/// Number of denials that could not be recorded due to lock contention.
+pub fn dropped_count() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+// ---------------------------------------------------------------------------
+// Capability inspection
+// ---------------------------------------------------------------------------
+
+/// Enumerate every capability in `cap_space`.
+pub fn snapshot_capabilities(cap_space: &CapabilitySpace) -> Vec<CapInfoRecord> {
+    let mut records = Vec::new();
+    let _ = cap_space.iter_capabilities(|entry| {
+        records.push(CapInfoRecord {
+            token: entry.capability.to_u64(),
+            object_type: entry.object.type_code() as u32,
+            rights: entry.rights.bits() as u32,
+        });
+        true
+    });
+    records
+}
+
+/// Format a capability listing for `/proc/<pid>/caps`.
+pub fn format_capabilities(cap_space: &CapabilitySpace) -> String {
+    let mut out = String::from("token              type       rights\n");
+    for record in snapshot_capabilities(cap_space) {
+        out.push_str(&format!(
+            "{:#018x} {:<10} {}\n",
+            record.token,
+            object_type_name(record.object_type),
+            rights_string(record.rights)
+        ));
+    }
+    out
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    fn denial(seq: u64) -> CapDenialRecord {
+        CapDenialRecord {
+            seq,
+            subject: 7,
+            cap: 0x1234,
+            object_type: 3,
+            required: Rights::WRITE.bits() as u32,
+            held: Rights::READ.bits() as u32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ring_read_since() {
+        let mut ring = DenialRing::new();
+        for seq in 1..=5 {
+            ring.push(denial(seq));
+        }
+        let mut out = [CapDenialRecord::default(); 8];
+        assert_eq!(ring.read_since(0, &mut out), 5);
+        assert_eq!(out[0].seq, 1);
+        assert_eq!(ring.read_since(4, &mut out), 2);
+        assert_eq!(out[0].seq, 4);
+        assert_eq!(out[1].seq, 5);
+    }
+
+    #[test]
+    fn test_ring_wraps_and_keeps_newest() {
+        let mut ring = DenialRing::new();
+        let total = CAP_AUDIT_RING_SIZE as u64 + 10;
+        for seq in 1..=total {
+            ring.push(denial(seq));
+        }
+        let mut out = [CapDenialRecord::default(); CAP_AUDIT_RING_SIZE];
+        assert_eq!(ring.read_since(0, &mut out), CAP_AUDIT_RING_SIZE);
+        assert_eq!(out[0].seq, 11);
+        assert_eq!(out[CAP_AUDIT_RING_SIZE - 1].seq, total);
+    }
+
+    #[test]
+    fn test_record_denial_global() {
+        let since = next_sequence();
+        record_denial(42, 0xBEEF, 3, 2, 1);
+        let mut out = [CapDenialRecord::default(); 4];
+        let n = read_denials(since, &mut out);
+        assert!(n >= 1);
+        assert!(out[..n].iter().any(|r| r.subject == 42 && r.cap == 0xBEEF));
+    }
+
+    #[test]
+    fn test_rights_string() {
+        let rw = (Rights::READ.bits() | Rights::WRITE.bits()) as u32;
+        assert_eq!(rights_string(rw), "rw------");
+        assert_eq!(rights_string(0), "--------");
+    }
+
+    #[test]
+    fn test_object_type_names() {
+        assert_eq!(object_type_name(3), "endpoint");
+        assert_eq!(object_type_name(OBJECT_TYPE_NONE), "none");
+        assert_eq!(object_type_name(42), "unknown");
+    }
+}
@@ -1,6 +1,12 @@
 //! Memory Protection Features
 //!
 //! Implements ASLR, stack canaries, and other memory protection mechanisms.
+//!
+//! User processes get a randomized stack top, mmap base and PIE load base on
+//! every exec ([`user_layout`]), and no user mapping may be writable and
+//! executable at once unless the process is marked as a JIT
+//! ([`user_mapping_allowed`]). Booting with `norandmaps` restores the fixed
+//! layout.
 
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
@@ -57,9 +63,10 @@ impl Aslr {
     pub fn randomize_address(&self, base: usize, region_type: RegionType) -> usize {
         self.ensure_seeded();
         let entropy = {
-            let pool = self.entropy_pool.read();
+            let mut pool = self.entropy_pool.write();
             let index = (self.counter.fetch_add(1, Ordering::Relaxed) % 16) as usize;
-            pool[index]
+            // Refill the slot so successive layouts never reuse a value
+            core::mem::replace(&mut pool[index], get_random().next_u64())
         };
 
         let randomization_bits = match region_type {
@@ -116,6 +123,47 @@ pub enum RegionType {
     Mmap,
 }
 
+/// Top of the main thread's stack in the fixed (non-randomized) layout.
+pub const USER_STACK_TOP: usize = 0x7FFF_FFFF_0000;
+/// Start of the mmap area in the fixed layout.
+pub const USER_MMAP_BASE: usize = 0x4000_0000_0000;
+/// Load base of position-independent executables in the fixed layout.
+pub const USER_PIE_BASE: usize = 0x5555_5555_0000;
+
+/// Placement of the regions of a freshly exec'd user image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserLayout {
+    /// Top of the main thread's stack (the stack grows down from here)
+    pub stack_top: usize,
+    /// First address handed out by kernel-chosen mmap
+    pub mmap_base: usize,
+    /// Load base for ET_DYN executables
+    pub pie_base: usize,
+}
+
+impl UserLayout {
+    /// The fixed layout used when ASLR is disabled.
+    pub const fn fixed() -> Self {
+        Self {
+            stack_top: USER_STACK_TOP,
+            mmap_base: USER_MMAP_BASE,
+            pie_base: USER_PIE_BASE,
+        }
+    }
+
+    /// A layout with every region slid by an independent random offset.
+    ///
+    /// The stack slides down from [`USER_STACK_TOP`]; the mmap and PIE bases
+    /// slide up. The ranges are small enough that the regions never meet.
+    pub fn randomized(aslr: &Aslr) -> Self {
+        Self {
+            stack_top: USER_STACK_TOP - aslr.randomize_address(0, RegionType::Stack),
+            mmap_base: aslr.randomize_address(USER_MMAP_BASE, RegionType::Mmap),
+            pie_base: aslr.randomize_address(USER_PIE_BASE, RegionType::Executable),
+        }
+    }
+}
+
 /// Stack canary for detecting buffer overflows
 pub struct StackCanary {
     /// Canary value
@@ -423,6 +471,7 @@ impl Default for Kpti {
 /// Memory protection manager
 pub struct MemoryProtection {
     aslr: Aslr,
+    aslr_enabled: AtomicBool,
     stack_canaries_enabled: bool,
     guard_pages_enabled: bool,
     dep_enabled: bool, // Data Execution Prevention
@@ -436,6 +485,7 @@ impl MemoryProtection {
     pub fn new() -> Result<Self, KernelError> {
         Ok(Self {
             aslr: Aslr::new()?,
+            aslr_enabled: AtomicBool::new(true),
             stack_canaries_enabled: true,
             guard_pages_enabled: true,
             dep_enabled: true,
@@ -450,6 +500,33 @@ impl MemoryProtection {
         &self.aslr
     }
 
+    /// Enable/disable randomization of user address-space layouts
+    pub fn set_aslr(&self, enabled: bool) {
+        self.aslr_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Check if user address-space layouts are randomized
+    pub fn aslr_enabled(&self) -> bool {
+        self.aslr_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Layout for a new user image
+    pub fn user_layout(&self) -> UserLayout {
+        if self.aslr_enabled() {
+            UserLayout::randomized(&self.aslr)
+        } else {
+            UserLayout::fixed()
+        }
+    }
+
+    /// Check a user mapping against W^X.
+    ///
+    /// `wx_exempt` processes (JITs) may map pages writable and executable;
+    /// for everyone else such a request is counted as a violation and refused.
+    pub fn check_user_mapping(&self, writable: bool, executable: bool, wx_exempt: bool) -> bool {
+        wx_exempt || self.wx_policy.check_flags(writable, executable)
+    }
+
     /// Enable/disable stack canaries
     pub fn set_stack_canaries(&mut self, enabled: bool) {
         self.stack_canaries_enabled = enabled;
@@ -532,6 +609,11 @@ pub fn init() -> Result<(), KernelError> {
             id: 0,
         })?;
 
+    if crate::utils::cmdline::param("norandmaps").is_some() {
+        get_memory_protection().set_aslr(false);
+        crate::println!("[MEMORY-PROTECTION] ASLR disabled (norandmaps)");
+    }
+
     crate::println!("[MEMORY-PROTECTION] ASLR, stack canaries, and guard pages enabled");
     Ok(())
 }
@@ -543,6 +625,24 @@ pub fn get_memory_protection() -> &'static MemoryProtection {
         .expect("Memory protection not initialized")
 }
 
+/// Layout for a new user image. Falls back to the fixed layout before
+/// memory protection is initialized.
+pub fn user_layout() -> UserLayout {
+    MEMORY_PROTECTION
+        .get()
+        .map_or(UserLayout::fixed(), MemoryProtection::user_layout)
+}
+
+/// Whether a user mapping with the given permissions may be created.
+///
+/// See [`MemoryProtection::check_user_mapping`].
+pub fn user_mapping_allowed(writable: bool, executable: bool, wx_exempt: bool) -> bool {
+    match MEMORY_PROTECTION.get() {
+        Some(mp) => mp.check_user_mapping(writable, executable, wx_exempt),
+        None => wx_exempt || !(writable && executable),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -563,6 +663,39 @@ mod tests {
         assert_eq!(addr2 & 0xFFF, 0);
     }
 
+    #[test]
+    fn test_user_layout_randomized() {
+        let aslr = Aslr::new().unwrap();
+        let a = UserLayout::randomized(&aslr);
+        let b = UserLayout::randomized(&aslr);
+        assert_ne!(a, b);
+
+        for layout in [a, b] {
+            assert_eq!(layout.stack_top & 0xFFF, 0);
+            assert!(layout.stack_top <= USER_STACK_TOP);
+            assert!(layout.stack_top > USER_PIE_BASE + (1 << 28));
+            assert!(layout.mmap_base >= USER_MMAP_BASE);
+            assert!(layout.mmap_base < USER_PIE_BASE);
+            assert!(layout.pie_base >= USER_PIE_BASE);
+        }
+    }
+
+    #[test]
+    fn test_user_mapping_wx() {
+        let mp = MemoryProtection::new().unwrap();
+        assert!(mp.check_user_mapping(true, false, false));
+        assert!(mp.check_user_mapping(false, true, false));
+        assert!(!mp.check_user_mapping(true, true, false));
+        assert_eq!(mp.wx_policy().violation_count(), 1);
+
+        // JIT-capable processes are exempt and not counted
+        assert!(mp.check_user_mapping(true, true, true));
+        assert_eq!(mp.wx_policy().violation_count(), 1);
+
+        mp.set_aslr(false);
+        assert_eq!(mp.user_layout(), UserLayout::fixed());
+    }
+
     #[test]
     fn test_stack_canary() {
         let canary = StackCanary::new();
@@ -5,19 +5,24 @@
 //! - Mandatory Access Control (MAC)
 //! - Security audit framework
 //! - Secure boot verification
+//! - Per-application sandbox profiles
+//! - Syscall filters
 
 pub mod audit;
 pub mod audit_enhanced;
 pub mod auth;
 pub mod boot;
+pub mod cap_audit;
 pub mod dilithium;
 pub mod fuzzing;
 pub mod kaslr;
 pub mod mac;
 pub mod memory_protection;
+pub mod sandbox;
 pub mod smep_smap;
 pub mod spectre;
 pub mod stack_canary;
+pub mod syscall_filter;
 pub mod tpm;
 pub mod tpm_commands;
 
@@ -0,0 +1,529 @@
+//! Application sandbox profiles
+//!
+//! A sandbox profile confines the programs it names to the filesystem
+//! subtrees, IPC services and network access it lists. Profiles are one
+//! TOML file each in [`PROFILE_DIR`]; `pkgd` writes one for every package
+//! that is not core-trusted, so a third-party `.vpk` cannot touch anything
+//! its manifest did not ask for.
+//!
+//! ```toml
+//! [sandbox]
+//! programs = ["/usr/bin/notes", "/opt/notes/"]  # exact path or directory/
+//! read = ["/usr/share/notes", "/etc/fonts"]      # read, list and execute
+//! write = ["/home/user/.notes"]                  # create, modify, remove
+//! services = ["compositor", "clipboard"]         # IPC names to look up or bind
+//! network = "client"                             # "none" (default), "client"
+//!                                                # (connect) or "server" (listen)
+//! ```
+//!
+//! The loader looks the profile up on every exec (and for programs the
+//! kernel starts itself) and attaches it to the process. A process keeps
+//! its profile across exec and passes it to its children, so a sandbox is
+//! never widened or dropped; only a process that has none picks up the
+//! profile of the program it runs.
+//!
+//! Enforcement happens at syscall time on top of the usual permission
+//! checks: paths are made absolute, `.` and `..` are resolved and symlinks
+//! are followed the way the VFS follows them, and the result must lie in
+//! an allowed subtree. Every program may also read the shared libraries
+//! and the harmless device nodes in [`BASE_READ`], and its own
+//! executables. Profiles themselves are never writable from inside a
+//! sandbox. Denials are recorded in the audit log; filesystem denials fail
+//! with `EACCES`, and an unlisted service looks exactly like one that is
+//! not running.
+
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+
+use super::AccessType;
+use crate::{
+    error::KernelError,
+    fs::{NodeType, SYMLINK_MAX_DEPTH},
+    process::Process,
+    services::unit_file::{parse_toml, Value},
+};
+
+/// Directory holding the profiles.
+pub const PROFILE_DIR: &str = "/etc/sandbox";
+
+/// Profile file name suffix.
+pub const PROFILE_SUFFIX: &str = ".toml";
+
+/// Readable by every sandboxed program.
+pub const BASE_READ: &[&str] = &[
+    "/lib",
+    "/usr/lib",
+    "/dev/null",
+    "/dev/zero",
+    "/dev/random",
+    "/dev/urandom",
+];
+
+/// Writable by every sandboxed program.
+pub const BASE_WRITE: &[&str] = &["/dev/null"];
+
+/// Network access granted by a profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Network {
+    /// No IP sockets.
+    None,
+    /// Outgoing connections.
+    Client,
+    /// Outgoing connections, and binding and listening on ports.
+    Server,
+}
+
+impl Network {
+    /// The profile's spelling of this setting.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Network::None => "none",
+            Network::Client => "client",
+            Network::Server => "server",
+        }
+    }
+}
+
+/// A parsed sandbox profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// File name without [`PROFILE_SUFFIX`].
+    pub name: String,
+    /// Executables the profile applies to; entries ending in `/` match
+    /// everything below that directory.
+    pub programs: Vec<String>,
+    pub read: Vec<String>,
+    pub write: Vec<String>,
+    pub services: Vec<String>,
+    pub network: Network,
+}
+
+fn invalid(value: &'static str) -> KernelError {
+    KernelError::InvalidArgument {
+        name: "sandbox profile",
+        value,
+    }
+}
+
+/// Resolve `.` and `..` in the absolute `path` without touching the
+/// filesystem. The result has no trailing `/` except for the root.
+pub fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() {
+        return String::from("/");
+    }
+    let mut out = String::new();
+    for part in parts {
+        out.push('/');
+        out.push_str(part);
+    }
+    out
+}
+
+/// Whether `path` is `dir` or lies below it. Both are normalized.
+fn within(path: &str, dir: &str) -> bool {
+    dir == "/"
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// A normalized absolute path from a profile entry.
+fn profile_path(path: String) -> Result<String, KernelError> {
+    if !path.starts_with('/') {
+        return Err(invalid("paths must be absolute"));
+    }
+    Ok(normalize(&path))
+}
+
+impl Profile {
+    /// Parse the profile `text` stored as `name`.
+    pub fn parse(name: &str, text: &str) -> Result<Profile, KernelError> {
+        let mut profile = Profile {
+            name: String::from(name),
+            programs: Vec::new(),
+            read: Vec::new(),
+            write: Vec::new(),
+            services: Vec::new(),
+            network: Network::None,
+        };
+
+        parse_toml(text, |section, key, value: Value| {
+            match (section, key) {
+                ("sandbox", "programs") => {
+                    for program in value.list()? {
+                        let dir = program.ends_with('/');
+                        let mut path = profile_path(program)?;
+                        if dir && path != "/" {
+                            path.push('/');
+                        }
+                        profile.programs.push(path);
+                    }
+                }
+                ("sandbox", kind @ ("read" | "write")) => {
+                    for path in value.list()? {
+                        let path = profile_path(path)?;
+                        if kind == "read" {
+                            profile.read.push(path);
+                        } else {
+                            profile.write.push(path);
+                        }
+                    }
+                }
+                ("sandbox", "services") => {
+                    for service in value.list()? {
+                        if service.is_empty()
+                            || service.len() > crate::ipc::namespace::SERVICE_NAME_MAX
+                        {
+                            return Err(invalid("bad service name"));
+                        }
+                        profile.services.push(service);
+                    }
+                }
+                ("sandbox", "network") => {
+                    profile.network = match value.string()?.as_str() {
+                        "none" => Network::None,
+                        "client" => Network::Client,
+                        "server" => Network::Server,
+                        _ => return Err(invalid("network must be none, client or server")),
+                    }
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+
+        if profile.programs.is_empty() {
+            return Err(invalid("missing [sandbox] programs"));
+        }
+        Ok(profile)
+    }
+
+    /// Whether the profile confines the program at normalized `path`.
+    pub fn applies_to(&self, path: &str) -> bool {
+        self.programs
+            .iter()
+            .any(|program| match program.strip_suffix('/') {
+                Some(dir) => within(path, if dir.is_empty() { "/" } else { dir }),
+                None => program == path,
+            })
+    }
+
+    /// Whether `access` to the normalized, symlink-free `path` is allowed.
+    pub fn allows_path(&self, path: &str, access: AccessType) -> bool {
+        let under = |dir: &String| within(path, dir);
+        let base = |dirs: &[&str]| dirs.iter().any(|dir| within(path, dir));
+        match access {
+            AccessType::Write => {
+                !within(path, PROFILE_DIR) && (self.write.iter().any(under) || base(BASE_WRITE))
+            }
+            AccessType::Read | AccessType::Execute => {
+                self.applies_to(path)
+                    || self.read.iter().chain(&self.write).any(under)
+                    || base(BASE_READ)
+            }
+        }
+    }
+
+    /// Whether the IPC service `name` may be looked up or bound.
+    pub fn allows_service(&self, name: &str) -> bool {
+        self.services.iter().any(|service| service == name)
+    }
+
+    /// Whether the profile grants at least `need`.
+    pub fn allows_network(&self, need: Network) -> bool {
+        self.network >= need
+    }
+}
+
+/// The path the VFS ends up at for the absolute `path`: `.` and `..` are
+/// resolved lexically, as the VFS does, and symlinks are replaced by their
+/// targets. With `follow_last` unset a final symlink is kept, as for
+/// `lstat()` or `unlink()`. Components that do not exist yet are kept as
+/// they are. `None` if the symlinks nest too deeply to resolve.
+pub fn real_path(path: &str, follow_last: bool) -> Option<String> {
+    let mut path = normalize(path);
+    let Some(vfs) = crate::fs::try_get_vfs() else {
+        return Some(path);
+    };
+    let vfs = vfs.read();
+    let mut depth = 0;
+    'restart: loop {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        let mut prefix = String::new();
+        for (i, component) in components.iter().enumerate() {
+            prefix.push('/');
+            prefix.push_str(component);
+            if i + 1 == components.len() && !follow_last {
+                break;
+            }
+            let Ok(node) = vfs.resolve_path_no_follow(&prefix) else {
+                break;
+            };
+            if node.node_type() != NodeType::Symlink {
+                continue;
+            }
+            let Ok(target) = node.readlink() else {
+                break;
+            };
+            depth += 1;
+            if depth > SYMLINK_MAX_DEPTH {
+                return None;
+            }
+            // The VFS resolves relative link targets from the root.
+            let rest = components[i + 1..].join("/");
+            path = normalize(&format!("/{}/{}", target, rest));
+            continue 'restart;
+        }
+        return Some(path);
+    }
+}
+
+/// Name and contents of every profile in [`PROFILE_DIR`]; none when the
+/// directory is missing.
+fn profile_files() -> Vec<(String, Vec<u8>)> {
+    let mut files = Vec::new();
+    let Some(vfs) = crate::fs::try_get_vfs() else {
+        return files;
+    };
+    let Ok(entries) = vfs
+        .read()
+        .resolve_path(PROFILE_DIR)
+        .and_then(|dir| dir.readdir())
+    else {
+        return files;
+    };
+    for entry in entries {
+        let Some(name) = entry.name.strip_suffix(PROFILE_SUFFIX) else {
+            continue;
+        };
+        if entry.node_type != NodeType::File {
+            continue;
+        }
+        if let Ok(data) = crate::fs::read_file(&format!("{}/{}", PROFILE_DIR, entry.name)) {
+            files.push((String::from(name), data));
+        }
+    }
+    files
+}
+
+fn parse_file(name: &str, data: &[u8]) -> Result<Profile, KernelError> {
+    let text = core::str::from_utf8(data).map_err(|_| invalid("not UTF-8"))?;
+    Profile::parse(name, text)
+}
+
+/// Parse every profile in [`PROFILE_DIR`], for listing and validation.
+pub fn load_profiles() -> Vec<(String, Result<Profile, KernelError>)> {
+    profile_files()
+        .into_iter()
+        .map(|(name, data)| {
+            let parsed = parse_file(&name, &data);
+            (name, parsed)
+        })
+        .collect()
+}
+
+/// Find the profile for the program at absolute `path`, matching both the
+/// path it was started by and the file it resolves to.
+///
+/// A profile that fails to parse is skipped with a warning, unless it
+/// names the program: then the exec is refused rather than run the program
+/// unconfined.
+pub fn profile_for(path: &str) -> Result<Option<Arc<Profile>>, KernelError> {
+    let named = normalize(path);
+    let real = real_path(path, true).unwrap_or_else(|| named.clone());
+    for (name, data) in profile_files() {
+        match parse_file(&name, &data) {
+            Ok(profile) => {
+                if profile.applies_to(&named) || profile.applies_to(&real) {
+                    return Ok(Some(Arc::new(profile)));
+                }
+            }
+            Err(e) => {
+                let mentions = |p: &str| {
+                    let needle = format!("\"{}\"", p);
+                    data.windows(needle.len()).any(|w| w == needle.as_bytes())
+                };
+                if mentions(&named) || mentions(&real) {
+                    crate::println!(
+                        "[SANDBOX] {}/{}{}: {:?}; refusing to run {}",
+                        PROFILE_DIR,
+                        name,
+                        PROFILE_SUFFIX,
+                        e,
+                        path
+                    );
+                    return Err(KernelError::PermissionDenied {
+                        operation: "exec with a broken sandbox profile",
+                    });
+                }
+                crate::println!(
+                    "[SANDBOX] Skipping {}/{}{}: {:?}",
+                    PROFILE_DIR,
+                    name,
+                    PROFILE_SUFFIX,
+                    e
+                );
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Confine `process` by `profile` unless it is already sandboxed.
+pub fn install(process: &Process, profile: Option<Arc<Profile>>) {
+    let mut sandbox = process.sandbox.lock();
+    if sandbox.is_none() {
+        if let Some(profile) = &profile {
+            crate::println!(
+                "[SANDBOX] PID {} runs under profile {}",
+                process.pid.0,
+                profile.name
+            );
+        }
+        *sandbox = profile;
+    }
+}
+
+fn deny(process: &Process, profile: &Profile, what: &str) -> KernelError {
+    crate::security::audit::log_permission_denied(
+        process.pid.0,
+        process.credentials().euid,
+        &format!("sandbox {}: {}", profile.name, what),
+    );
+    KernelError::PermissionDenied {
+        operation: "sandbox",
+    }
+}
+
+/// Check `access` to the absolute `path` against `process`'s profile.
+pub fn check_path(
+    process: &Process,
+    path: &str,
+    access: AccessType,
+    follow_last: bool,
+) -> Result<(), KernelError> {
+    let Some(profile) = process.sandbox.lock().clone() else {
+        return Ok(());
+    };
+    let allowed =
+        real_path(path, follow_last).is_some_and(|real| profile.allows_path(&real, access));
+    if allowed {
+        return Ok(());
+    }
+    let what = match access {
+        AccessType::Read => "read",
+        AccessType::Write => "write",
+        AccessType::Execute => "exec",
+    };
+    Err(deny(process, &profile, &format!("{} {}", what, path)))
+}
+
+/// Check that `process`'s profile grants `need`.
+pub fn check_network(process: &Process, need: Network) -> Result<(), KernelError> {
+    match process.sandbox.lock().clone() {
+        Some(profile) if !profile.allows_network(need) => Err(deny(
+            process,
+            &profile,
+            &format!("network {}", need.as_str()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Check that `process` may use the IPC service `name`.
+pub fn check_service(process: &Process, name: &str) -> Result<(), KernelError> {
+    match process.sandbox.lock().clone() {
+        Some(profile) if !profile.allows_service(name) => {
+            Err(deny(process, &profile, &format!("service {}", name)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Sandboxed processes may not trace others: reading or writing another
+/// process's memory would escape the profile.
+pub fn check_ptrace(process: &Process, target: u64) -> Result<(), KernelError> {
+    match process.sandbox.lock().clone() {
+        Some(profile) => Err(deny(process, &profile, &format!("ptrace pid {}", target))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str = r#"
+[sandbox]
+programs = ["/usr/bin/notes", "/opt/notes/"]
+read = ["/usr/share/notes/", "/etc/fonts"]
+write = ["/home/user/.notes"]
+services = ["compositor"]
+network = "client"
+"#;
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = Profile::parse("notes", NOTES).unwrap();
+        assert_eq!(profile.programs, ["/usr/bin/notes", "/opt/notes/"]);
+        assert_eq!(profile.read, ["/usr/share/notes", "/etc/fonts"]);
+        assert_eq!(profile.network, Network::Client);
+        assert!(profile.allows_network(Network::Client));
+        assert!(!profile.allows_network(Network::Server));
+        assert!(profile.allows_service("compositor"));
+        assert!(!profile.allows_service("blockdev"));
+
+        assert!(Profile::parse("x", "[sandbox]\nread = [\"/tmp\"]\n").is_err());
+        assert!(Profile::parse("x", "[sandbox]\nprograms = [\"bin/x\"]\n").is_err());
+        assert!(
+            Profile::parse("x", "[sandbox]\nprograms = [\"/x\"]\nnetwork = \"all\"\n").is_err()
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("/"), "/");
+        assert_eq!(normalize("/a/./b//c/"), "/a/b/c");
+        assert_eq!(normalize("/a/b/../../../etc"), "/etc");
+    }
+
+    #[test]
+    fn test_path_access() {
+        let profile = Profile::parse("notes", NOTES).unwrap();
+        let read = |p: &str| profile.allows_path(&normalize(p), AccessType::Read);
+        let write = |p: &str| profile.allows_path(&normalize(p), AccessType::Write);
+
+        assert!(read("/etc/fonts/fonts.conf"));
+        assert!(!read("/etc/fontsx"));
+        assert!(!read("/etc/fonts/../passwd"));
+        assert!(read("/home/user/.notes/today"));
+        assert!(write("/home/user/.notes"));
+        assert!(!write("/usr/share/notes/help"));
+        assert!(read("/lib/libc.so") && read("/dev/null") && write("/dev/null"));
+        assert!(!write("/dev/zero"));
+        assert!(profile.allows_path("/opt/notes/bin/helper", AccessType::Execute));
+
+        let open =
+            Profile::parse("open", "[sandbox]\nprograms = [\"/x\"]\nwrite = [\"/\"]\n").unwrap();
+        assert!(open.allows_path("/tmp/a", AccessType::Write));
+        assert!(!open.allows_path("/etc/sandbox/notes.toml", AccessType::Write));
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let profile = Profile::parse("notes", NOTES).unwrap();
+        assert!(profile.applies_to("/usr/bin/notes"));
+        assert!(!profile.applies_to("/usr/bin/notes2"));
+        assert!(profile.applies_to("/opt/notes/bin/notes"));
+        assert!(!profile.applies_to("/opt/notesx/bin"));
+    }
+}
@@ -0,0 +1,322 @@
+//! Syscall filters
+//!
+//! A filter narrows the syscalls a process may make, on top of what its
+//! capabilities and credentials allow: an allowlist or denylist of syscall
+//! numbers and groups (see `veridian_abi::syscall_filter`) and the action
+//! taken on a syscall the list rejects: kill the process, fail the call
+//! with `EPERM`, or log it and let it run.
+//!
+//! Filters are installed with `SYS_SYSCALL_FILTER`, by a process on itself
+//! or by its launcher on a child, and checked by the syscall entry path
+//! before any argument is looked at. They only ever add restrictions:
+//! every installed filter is consulted and the harshest verdict wins, they
+//! are inherited on fork and kept across exec, and nothing removes them. A
+//! filter installed "on exec" waits until the process next execs, so a
+//! launcher need not allow exec in the filter it hands its child.
+//!
+//! Exiting and returning from a signal handler are never filtered, so a
+//! filtered process can always wind down.
+
+use alloc::{format, sync::Arc, vec::Vec};
+
+use veridian_abi::{syscall_filter as abi, Syscall};
+
+use crate::{
+    error::KernelError,
+    process::{exit::signals::SIGKILL, Process},
+    security::audit::{self, AuditAction, AuditEvent, AuditEventType},
+};
+
+/// Most filters stacked on one process.
+pub const MAX_FILTERS: usize = 8;
+
+/// Syscall numbers a filter can name, past the end of the syscall table.
+const MAX_SYSCALL: usize = 512;
+
+/// Never filtered.
+const ALWAYS_ALLOWED: &[Syscall] = &[
+    Syscall::ProcessExit,
+    Syscall::ThreadExit,
+    Syscall::SigReturn,
+];
+
+/// What happens to a rejected syscall, mildest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    /// Logged, then run as usual.
+    Log,
+    /// Failed with `EPERM`.
+    Errno,
+    /// The process is killed.
+    Kill,
+}
+
+impl Action {
+    /// Decode an `ACTION_*` value.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            abi::ACTION_KILL => Some(Action::Kill),
+            abi::ACTION_ERRNO => Some(Action::Errno),
+            abi::ACTION_LOG => Some(Action::Log),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Log => "log",
+            Action::Errno => "errno",
+            Action::Kill => "kill",
+        }
+    }
+}
+
+/// One installed filter.
+#[derive(Debug)]
+pub struct Filter {
+    /// Bitmap of the listed syscall numbers.
+    listed: [u64; MAX_SYSCALL / 64],
+    /// The list is a denylist rather than an allowlist.
+    deny: bool,
+    pub action: Action,
+}
+
+impl Filter {
+    /// Build a filter from a `MODE_ALLOW` or `MODE_DENY` mode, an
+    /// `ACTION_*` action and a list of syscall numbers and groups.
+    pub fn new(mode: u32, action: u32, entries: &[u32]) -> Result<Self, KernelError> {
+        let invalid = |value| KernelError::InvalidArgument {
+            name: "syscall filter",
+            value,
+        };
+        let deny = match mode {
+            abi::MODE_ALLOW => false,
+            abi::MODE_DENY => true,
+            _ => return Err(invalid("unknown mode")),
+        };
+        let action = Action::from_raw(action).ok_or(invalid("unknown action"))?;
+        if entries.len() > abi::MAX_ENTRIES {
+            return Err(invalid("too many entries"));
+        }
+
+        let mut filter = Filter {
+            listed: [0; MAX_SYSCALL / 64],
+            deny,
+            action,
+        };
+        for &entry in entries {
+            if entry & abi::GROUP != 0 {
+                let group = abi::GROUPS
+                    .get((entry & !abi::GROUP) as usize)
+                    .ok_or(invalid("unknown group"))?;
+                for &syscall in group.syscalls {
+                    filter.list(syscall.number());
+                }
+            } else if (entry as usize) < MAX_SYSCALL {
+                filter.list(entry as usize);
+            } else {
+                return Err(invalid("syscall number out of range"));
+            }
+        }
+        Ok(filter)
+    }
+
+    fn list(&mut self, nr: usize) {
+        self.listed[nr / 64] |= 1 << (nr % 64);
+    }
+
+    fn is_listed(&self, nr: usize) -> bool {
+        nr < MAX_SYSCALL && self.listed[nr / 64] & (1 << (nr % 64)) != 0
+    }
+
+    /// Whether syscall `nr` is rejected by this filter.
+    pub fn rejects(&self, nr: usize) -> bool {
+        self.is_listed(nr) == self.deny
+    }
+
+    /// "allow" or "deny".
+    pub fn mode_str(&self) -> &'static str {
+        if self.deny {
+            "deny"
+        } else {
+            "allow"
+        }
+    }
+
+    /// Number of listed syscalls.
+    pub fn len(&self) -> usize {
+        self.listed.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The filters of one process.
+#[derive(Debug, Clone, Default)]
+pub struct Filters {
+    /// Checked on every syscall.
+    pub active: Vec<Arc<Filter>>,
+    /// Moved to `active` at the next exec.
+    pub on_exec: Vec<Arc<Filter>>,
+}
+
+impl Filters {
+    pub const fn new() -> Self {
+        Filters {
+            active: Vec::new(),
+            on_exec: Vec::new(),
+        }
+    }
+
+    /// Add `filter`, now or from the next exec on.
+    pub fn add(&mut self, filter: Filter, on_exec: bool) -> Result<(), KernelError> {
+        if self.active.len() + self.on_exec.len() >= MAX_FILTERS {
+            return Err(KernelError::ResourceExhausted {
+                resource: "syscall filters",
+            });
+        }
+        let filter = Arc::new(filter);
+        if on_exec {
+            self.on_exec.push(filter);
+        } else {
+            self.active.push(filter);
+        }
+        Ok(())
+    }
+
+    /// Arm the filters waiting for exec. Called by exec.
+    pub fn exec(&mut self) {
+        let pending = core::mem::take(&mut self.on_exec);
+        self.active.extend(pending);
+    }
+
+    /// The harshest action of the active filters rejecting syscall `nr`,
+    /// or `None` if they all let it through.
+    pub fn verdict(&self, nr: usize) -> Option<Action> {
+        if ALWAYS_ALLOWED.iter().any(|s| s.number() == nr) {
+            return None;
+        }
+        self.active
+            .iter()
+            .filter(|f| f.rejects(nr))
+            .map(|f| f.action)
+            .max()
+    }
+}
+
+/// Name of syscall `nr` for log messages.
+fn syscall_name(nr: usize) -> alloc::string::String {
+    match Syscall::try_from(nr) {
+        Ok(syscall) => syscall.name().into(),
+        Err(()) => format!("syscall {}", nr),
+    }
+}
+
+/// Check syscall `nr` of the current process against its filters. Called
+/// on syscall entry; an error means the syscall must not run.
+pub fn check(nr: usize) -> Result<(), KernelError> {
+    let Some(process) = crate::process::current_process() else {
+        return Ok(());
+    };
+    let verdict = {
+        let filters = process.syscall_filters.lock();
+        if filters.active.is_empty() {
+            return Ok(());
+        }
+        filters.verdict(nr)
+    };
+    match verdict {
+        None => Ok(()),
+        Some(Action::Log) => {
+            audit::log_event(AuditEvent::new(
+                AuditEventType::Syscall,
+                process.pid.0,
+                process.credentials().euid,
+                AuditAction::Other,
+                &syscall_name(nr),
+                true,
+                "syscall filter: log",
+            ));
+            Ok(())
+        }
+        Some(action) => Err(reject(process, nr, action)),
+    }
+}
+
+fn reject(process: &Process, nr: usize, action: Action) -> KernelError {
+    let name = syscall_name(nr);
+    crate::println!(
+        "[FILTER] PID {}: {} rejected ({})",
+        process.pid.0,
+        name,
+        action.as_str()
+    );
+    audit::log_permission_denied(
+        process.pid.0,
+        process.credentials().euid,
+        &format!("syscall filter: {}", name),
+    );
+    if action == Action::Kill {
+        let _ = process.send_signal(SIGKILL as usize);
+    }
+    KernelError::PermissionDenied {
+        operation: "filtered syscall",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nr(syscall: Syscall) -> usize {
+        syscall.number()
+    }
+
+    #[test]
+    fn test_allow_and_deny() {
+        let basic = abi::GROUP | abi::group("basic").unwrap() as u32;
+        let allow = Filter::new(abi::MODE_ALLOW, abi::ACTION_ERRNO, &[basic, 84]).unwrap();
+        assert!(!allow.rejects(nr(Syscall::FileRead)));
+        assert!(!allow.rejects(nr(Syscall::Watchdog)));
+        assert!(allow.rejects(nr(Syscall::FileOpen)));
+        assert!(allow.rejects(9999));
+
+        let deny = Filter::new(abi::MODE_DENY, abi::ACTION_KILL, &[85]).unwrap();
+        assert!(deny.rejects(nr(Syscall::Reboot)));
+        assert!(!deny.rejects(nr(Syscall::FileOpen)));
+        assert_eq!(deny.len(), 1);
+
+        assert!(Filter::new(2, abi::ACTION_KILL, &[]).is_err());
+        assert!(Filter::new(abi::MODE_DENY, 7, &[]).is_err());
+        assert!(Filter::new(abi::MODE_DENY, abi::ACTION_KILL, &[abi::GROUP | 99]).is_err());
+        assert!(Filter::new(abi::MODE_DENY, abi::ACTION_KILL, &[4096]).is_err());
+    }
+
+    #[test]
+    fn test_stacking() {
+        let deny = |action, entries: &[u32]| Filter::new(abi::MODE_DENY, action, entries).unwrap();
+        let admin = abi::GROUP | abi::group("admin").unwrap() as u32;
+        let mut filters = Filters::new();
+        filters.add(deny(abi::ACTION_LOG, &[admin]), false).unwrap();
+        filters.add(deny(abi::ACTION_KILL, &[85]), true).unwrap();
+        assert_eq!(filters.verdict(nr(Syscall::Reboot)), Some(Action::Log));
+        assert_eq!(filters.verdict(nr(Syscall::FileRead)), None);
+
+        filters.exec();
+        assert_eq!(filters.verdict(nr(Syscall::Reboot)), Some(Action::Kill));
+        assert_eq!(filters.verdict(nr(Syscall::FsMount)), Some(Action::Log));
+
+        // Nothing can stop a process from exiting
+        let nothing = Filter::new(abi::MODE_ALLOW, abi::ACTION_KILL, &[]).unwrap();
+        filters.add(nothing, false).unwrap();
+        assert_eq!(filters.verdict(nr(Syscall::ProcessExit)), None);
+        assert_eq!(filters.verdict(nr(Syscall::FileRead)), Some(Action::Kill));
+
+        for _ in 3..MAX_FILTERS {
+            filters.add(deny(abi::ACTION_LOG, &[]), false).unwrap();
+        }
+        assert!(filters.add(deny(abi::ACTION_LOG, &[]), false).is_err());
+    }
+}
@@ -30,9 +30,14 @@ macro_rules! serial_println {
     };
 }
 
-// Delegate to architecture-specific implementation
+// Goes through the console multiplexer once the console port is set up,
+// else straight to the architecture-specific implementation
 #[doc(hidden)]
 pub fn _serial_print(args: fmt::Arguments) {
+    if crate::drivers::serial::console::print(args) {
+        return;
+    }
+
     #[cfg(target_arch = "x86_64")]
     crate::arch::x86_64::serial::_serial_print(args);
 
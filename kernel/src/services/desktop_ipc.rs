@@ -29,6 +29,10 @@ pub const DESKTOP_NOTIFICATION_ENDPOINT: EndpointId = 1003;
 pub const DESKTOP_CLIPBOARD_ENDPOINT: EndpointId = 1004;
 /// Application launcher endpoint (app start, .desktop file queries)
 pub const DESKTOP_LAUNCHER_ENDPOINT: EndpointId = 1005;
+/// Power status endpoint (battery level, AC state, thermal zones)
+pub const DESKTOP_POWER_ENDPOINT: EndpointId = 1006;
+/// Desktop settings endpoint (theme, wallpaper, input preferences)
+pub const DESKTOP_SETTINGS_ENDPOINT: EndpointId = 1007;
 
 /// Legacy aliases for backward compatibility with Phase 6 code.
 pub const WINDOW_MANAGER_ENDPOINT: EndpointId = DESKTOP_WM_ENDPOINT;
@@ -435,6 +439,8 @@ impl DesktopIpcServer {
             (DESKTOP_NOTIFICATION_ENDPOINT, "notifications"),
             (DESKTOP_CLIPBOARD_ENDPOINT, "clipboard"),
             (DESKTOP_LAUNCHER_ENDPOINT, "launcher"),
+            (DESKTOP_POWER_ENDPOINT, "power"),
+            (DESKTOP_SETTINGS_ENDPOINT, "settings"),
         ];
 
         for &(id, name) in endpoints {
@@ -580,6 +586,8 @@ mod tests {
             DESKTOP_NOTIFICATION_ENDPOINT,
             DESKTOP_CLIPBOARD_ENDPOINT,
             DESKTOP_LAUNCHER_ENDPOINT,
+            DESKTOP_POWER_ENDPOINT,
+            DESKTOP_SETTINGS_ENDPOINT,
         ];
         for i in 0..ids.len() {
             for j in (i + 1)..ids.len() {
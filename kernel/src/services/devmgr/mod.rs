@@ -0,0 +1,900 @@
+//! Device manager
+//!
+//! Binds devices to drivers through driver manifests. A manifest is a TOML
+//! file named after its driver and kept next to the driver binary in
+//! [`DRIVER_DIR`] (`/drivers/virtio-net.toml` describes
+//! `/drivers/virtio-net`):
+//!
+//! ```toml
+//! [driver]
+//! description = "Virtio network device"
+//! exec = "/drivers/virtio-net"     # default: the binary next to the manifest
+//! args = ["-v"]
+//! requires = ["virtio-blk"]        # drivers that must be bound first
+//! capabilities = ["mmio", "irq"]   # "mmio", "ioport" and "irq"
+//!
+//! [match]                          # a device matching any entry is bound
+//! pci = ["1af4:1000", "1af4:1041"] # vendor:device, device may be "*"
+//! pci_class = ["02:00"]            # class:subclass, subclass may be "*"
+//! compatible = ["virtio,mmio"]     # device tree compatible string
+//! virtio = 1                       # with `compatible`: virtio device type
+//! ```
+//!
+//! Drivers built into the kernel carry embedded manifests and are bound by
+//! [`init`] during boot, before the root file system is loaded. User-space
+//! drivers are started by [`start_user_drivers`] once the scheduler runs:
+//! one process per device, holding capabilities for the device resources
+//! named in `capabilities`, with the device described in its environment
+//! (`DEVICE`, `DEVICE_MMIO=base:size,...`, `DEVICE_IOPORTS=base:size,...`,
+//! `DEVICE_IRQ`). A driver process that dies is restarted; see [`recovery`].
+
+use alloc::{format, string::String, vec::Vec};
+
+use spin::Mutex;
+
+pub mod recovery;
+
+pub use self::recovery::{driver_exited, poll};
+use super::unit_file::{parse_toml, valid_unit_name, UNIT_SUFFIX};
+use crate::{drivers::pci::PciLocation, error::KernelError, process::ProcessId};
+
+/// Directory holding user-space drivers and their manifests.
+pub const DRIVER_DIR: &str = "/drivers";
+
+/// Device resource a driver needs a capability for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// The device's MMIO regions.
+    Mmio,
+    /// The device's I/O port ranges.
+    IoPort,
+    /// The device's interrupt.
+    Irq,
+}
+
+/// One `[match]` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceMatch {
+    /// PCI vendor and device ID; `None` matches any device of the vendor.
+    Pci { vendor: u16, device: Option<u16> },
+    /// PCI class and subclass; `None` matches the whole class.
+    PciClass { class: u8, subclass: Option<u8> },
+    /// Device tree compatible string.
+    Compatible(String),
+}
+
+/// A parsed driver manifest.
+#[derive(Debug, Clone)]
+pub struct DriverManifest {
+    pub name: String,
+    pub description: String,
+    /// Driver binary; empty for a driver built into the kernel.
+    pub exec: String,
+    pub args: Vec<String>,
+    /// Drivers bound before this one.
+    pub requires: Vec<String>,
+    pub capabilities: Vec<Resource>,
+    pub matches: Vec<DeviceMatch>,
+    /// Virtio device type the device tree matches are restricted to.
+    pub virtio: Option<u32>,
+}
+
+/// How a device was found, and what identifies it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceIds {
+    Pci {
+        location: PciLocation,
+        vendor: u16,
+        device: u16,
+        class: u8,
+        subclass: u8,
+    },
+    Dt {
+        compatible: Vec<String>,
+        /// Virtio device type behind a `virtio,mmio` node.
+        virtio: Option<u32>,
+    },
+}
+
+/// A device to bind a driver to.
+#[derive(Debug, Clone)]
+pub struct Device {
+    /// `pci-00:04.0`, or the device tree node name.
+    pub name: String,
+    pub ids: DeviceIds,
+    /// `(base, size)` MMIO regions.
+    pub mmio: Vec<(u64, u64)>,
+    /// `(base, size)` I/O port ranges.
+    pub io_ports: Vec<(u16, u16)>,
+    pub irq: Option<u32>,
+}
+
+impl DriverManifest {
+    /// Whether this driver drives `device`.
+    pub fn matches(&self, device: &Device) -> bool {
+        self.matches.iter().any(|m| match (m, &device.ids) {
+            (
+                DeviceMatch::Pci { vendor, device: id },
+                DeviceIds::Pci {
+                    vendor: v,
+                    device: d,
+                    ..
+                },
+            ) => vendor == v && id.is_none_or(|id| id == *d),
+            (
+                DeviceMatch::PciClass { class, subclass },
+                DeviceIds::Pci {
+                    class: c,
+                    subclass: s,
+                    ..
+                },
+            ) => class == c && subclass.is_none_or(|sub| sub == *s),
+            (DeviceMatch::Compatible(name), DeviceIds::Dt { compatible, virtio }) => {
+                compatible.contains(name) && (self.virtio.is_none() || self.virtio == *virtio)
+            }
+            _ => false,
+        })
+    }
+}
+
+fn invalid(value: &'static str) -> KernelError {
+    KernelError::InvalidArgument {
+        name: "driver manifest",
+        value,
+    }
+}
+
+/// Parse `"hi:lo"` with hexadecimal halves, `lo` possibly `*`.
+fn parse_id_pair(s: &str) -> Option<(u16, Option<u16>)> {
+    let (hi, lo) = s.split_once(':')?;
+    let hi = u16::from_str_radix(hi, 16).ok()?;
+    let lo = match lo {
+        "*" => None,
+        lo => Some(u16::from_str_radix(lo, 16).ok()?),
+    };
+    Some((hi, lo))
+}
+
+/// Parse the manifest `text` of driver `name`.
+///
+/// A manifest without `exec` runs the binary of the same name in
+/// [`DRIVER_DIR`]; see [`builtin_manifests`] for drivers in the kernel.
+pub fn parse_manifest(name: &str, text: &str) -> Result<DriverManifest, KernelError> {
+    if !valid_unit_name(name) {
+        return Err(invalid("bad driver name"));
+    }
+    let mut manifest = DriverManifest {
+        name: String::from(name),
+        description: String::new(),
+        exec: format!("{}/{}", DRIVER_DIR, name),
+        args: Vec::new(),
+        requires: Vec::new(),
+        capabilities: Vec::new(),
+        matches: Vec::new(),
+        virtio: None,
+    };
+
+    parse_toml(text, |section, key, value| {
+        match (section, key) {
+            ("driver", "description") => manifest.description = value.string()?,
+            ("driver", "exec") => manifest.exec = value.string()?,
+            ("driver", "args") => manifest.args = value.list()?,
+            ("driver", "requires") => {
+                for driver in value.list()? {
+                    if !valid_unit_name(&driver) {
+                        return Err(invalid("bad dependency name"));
+                    }
+                    manifest.requires.push(driver);
+                }
+            }
+            ("driver", "capabilities") => {
+                for resource in value.list()? {
+                    manifest.capabilities.push(match resource.as_str() {
+                        "mmio" => Resource::Mmio,
+                        "ioport" => Resource::IoPort,
+                        "irq" => Resource::Irq,
+                        _ => return Err(invalid("capabilities must be mmio, ioport or irq")),
+                    });
+                }
+            }
+            ("match", "pci") => {
+                for id in value.list()? {
+                    let (vendor, device) =
+                        parse_id_pair(&id).ok_or(invalid("pci must be \"vendor:device\""))?;
+                    manifest.matches.push(DeviceMatch::Pci { vendor, device });
+                }
+            }
+            ("match", "pci_class") => {
+                for id in value.list()? {
+                    let (class, subclass) = parse_id_pair(&id)
+                        .filter(|&(class, subclass)| class <= 0xFF && subclass.unwrap_or(0) <= 0xFF)
+                        .ok_or(invalid("pci_class must be \"class:subclass\""))?;
+                    manifest.matches.push(DeviceMatch::PciClass {
+                        class: class as u8,
+                        subclass: subclass.map(|s| s as u8),
+                    });
+                }
+            }
+            ("match", "compatible") => manifest
+                .matches
+                .extend(value.list()?.into_iter().map(DeviceMatch::Compatible)),
+            ("match", "virtio") => manifest.virtio = Some(value.int()?),
+            _ => {}
+        }
+        Ok(())
+    })
+    .map_err(|e| match e {
+        KernelError::InvalidArgument { value, .. } => invalid(value),
+        e => e,
+    })?;
+
+    if manifest.matches.is_empty() {
+        return Err(invalid("missing [match] entries"));
+    }
+    Ok(manifest)
+}
+
+/// Order in which to bind `manifests` so that every driver comes after the
+/// drivers it requires. Returns the order and the drivers left out because
+/// a requirement is missing or circular; requirements already in `bound`
+/// count as met.
+pub fn bind_order(manifests: &[DriverManifest], bound: &[String]) -> (Vec<usize>, Vec<usize>) {
+    let mut order: Vec<usize> = Vec::new();
+    let mut pending: Vec<usize> = (0..manifests.len()).collect();
+    loop {
+        let before = pending.len();
+        pending.retain(|&i| {
+            let ready = manifests[i]
+                .requires
+                .iter()
+                .all(|req| bound.contains(req) || order.iter().any(|&j| manifests[j].name == *req));
+            if ready {
+                order.push(i);
+            }
+            !ready
+        });
+        if pending.is_empty() || pending.len() == before {
+            return (order, pending);
+        }
+    }
+}
+
+/// Environment describing `device` to its driver process.
+fn driver_env(device: &Device) -> Vec<String> {
+    let mut env = Vec::new();
+    env.push(format!("DEVICE={}", device.name));
+    let ranges = |ranges: &mut dyn Iterator<Item = (u64, u64)>| {
+        ranges
+            .map(|(base, size)| format!("{:#x}:{:#x}", base, size))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    if !device.mmio.is_empty() {
+        env.push(format!(
+            "DEVICE_MMIO={}",
+            ranges(&mut device.mmio.iter().copied())
+        ));
+    }
+    if !device.io_ports.is_empty() {
+        env.push(format!(
+            "DEVICE_IOPORTS={}",
+            ranges(
+                &mut device
+                    .io_ports
+                    .iter()
+                    .map(|&(base, size)| (base as u64, size as u64))
+            )
+        ));
+    }
+    if let Some(irq) = device.irq {
+        env.push(format!("DEVICE_IRQ={}", irq));
+    }
+    env
+}
+
+// ---------------------------------------------------------------------------
+// Built-in drivers
+// ---------------------------------------------------------------------------
+
+/// A driver built into the kernel.
+struct Builtin {
+    name: &'static str,
+    manifest: &'static str,
+    attach: fn(&Device) -> Result<(), KernelError>,
+}
+
+const BUILTIN: &[Builtin] = &[
+    Builtin {
+        name: "virtio-blk",
+        manifest: r#"
+[driver]
+description = "Virtio block device"
+
+[match]
+pci = ["1af4:1001", "1af4:1042"]
+compatible = ["virtio,mmio"]
+virtio = 2
+"#,
+        attach: attach_virtio_blk,
+    },
+    Builtin {
+        name: "virtio-console",
+        manifest: r#"
+[driver]
+description = "Virtio console serial ports"
+
+[match]
+pci = ["1af4:1003", "1af4:1043"]
+compatible = ["virtio,mmio"]
+virtio = 3
+"#,
+        attach: attach_virtio_console,
+    },
+    Builtin {
+        name: "virtio-9p",
+        manifest: r#"
+[driver]
+description = "Virtio 9P host file sharing"
+
+[match]
+pci = ["1af4:1009", "1af4:1049"]
+compatible = ["virtio,mmio"]
+virtio = 9
+"#,
+        attach: attach_virtio_9p,
+    },
+];
+
+/// The enumerated PCI function behind `location`.
+#[cfg(target_arch = "x86_64")]
+fn pci_device(
+    location: crate::drivers::pci::PciLocation,
+) -> Result<crate::drivers::pci::PciDevice, KernelError> {
+    crate::drivers::pci::get_pci_bus()
+        .lock()
+        .get_device(location)
+        .ok_or(KernelError::NotFound {
+            resource: "PCI device",
+            id: 0,
+        })
+}
+
+/// Base of the first MMIO region of `device`.
+fn mmio_base(device: &Device) -> Result<usize, KernelError> {
+    let &(base, _) = device.mmio.first().ok_or(KernelError::NotFound {
+        resource: "MMIO region",
+        id: 0,
+    })?;
+    Ok(base as usize)
+}
+
+fn attach_virtio_blk(device: &Device) -> Result<(), KernelError> {
+    #[cfg(target_arch = "x86_64")]
+    if let DeviceIds::Pci { location, .. } = device.ids {
+        return crate::drivers::virtio::blk::attach_pci(&pci_device(location)?);
+    }
+    crate::drivers::virtio::blk::attach_mmio(mmio_base(device)?)
+}
+
+fn attach_virtio_console(device: &Device) -> Result<(), KernelError> {
+    #[cfg(target_arch = "x86_64")]
+    if let DeviceIds::Pci { location, .. } = device.ids {
+        return crate::drivers::virtio::console::attach_pci(&pci_device(location)?);
+    }
+    crate::drivers::virtio::console::attach_mmio(mmio_base(device)?)
+}
+
+fn attach_virtio_9p(device: &Device) -> Result<(), KernelError> {
+    #[cfg(target_arch = "x86_64")]
+    if let DeviceIds::Pci { location, .. } = device.ids {
+        return crate::drivers::virtio::p9::attach_pci(&pci_device(location)?);
+    }
+    crate::drivers::virtio::p9::attach_mmio(mmio_base(device)?)
+}
+
+/// Manifests of the drivers built into the kernel.
+pub fn builtin_manifests() -> Vec<DriverManifest> {
+    BUILTIN
+        .iter()
+        .map(|builtin| {
+            let mut manifest = parse_manifest(builtin.name, builtin.manifest)
+                .expect("built-in driver manifest is valid");
+            manifest.exec.clear();
+            manifest
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Devices and bindings
+// ---------------------------------------------------------------------------
+
+/// PCI devices, from the enumerated PCI bus.
+#[cfg(target_arch = "x86_64")]
+fn pci_devices() -> Vec<Device> {
+    use crate::drivers::pci::{self, PciBar};
+
+    if !pci::is_pci_initialized() {
+        return Vec::new();
+    }
+    let all_devices = pci::get_pci_bus().lock().get_all_devices();
+    all_devices
+        .into_iter()
+        .map(|dev| {
+            let loc = dev.location;
+            let mut mmio = Vec::new();
+            let mut io_ports = Vec::new();
+            for bar in &dev.bars {
+                match *bar {
+                    PciBar::Memory { address, size, .. } => mmio.push((address, size)),
+                    PciBar::Io { address, size } => io_ports.push((address as u16, size as u16)),
+                    PciBar::None => {}
+                }
+            }
+            Device {
+                name: format!("pci-{:02x}:{:02x}.{}", loc.bus, loc.device, loc.function),
+                ids: DeviceIds::Pci {
+                    location: loc,
+                    vendor: dev.vendor_id,
+                    device: dev.device_id,
+                    class: dev.class_code,
+                    subclass: dev.subclass,
+                },
+                mmio,
+                io_ports,
+                irq: (dev.interrupt_pin != 0 && dev.interrupt_line != 0xFF)
+                    .then_some(dev.interrupt_line as u32),
+            }
+        })
+        .collect()
+}
+
+/// Device tree devices, or the QEMU `virt` virtio-mmio slots when the
+/// firmware passed no device tree.
+#[cfg(not(target_arch = "x86_64"))]
+fn dt_devices() -> Vec<Device> {
+    use crate::drivers::{dt, virtio::mmio};
+
+    let virtio_type = |regs: &[(u64, u64)]| {
+        regs.first()
+            .and_then(|&(base, _)| mmio::VirtioMmioTransport::new(base as usize).device_type())
+    };
+
+    let nodes = dt::nodes();
+    if nodes.is_empty() {
+        return mmio::DEFAULT_BASES
+            .iter()
+            .map(|&base| {
+                let mmio = alloc::vec![(base as u64, 0x200)];
+                Device {
+                    name: format!("virtio_mmio@{:x}", base),
+                    ids: DeviceIds::Dt {
+                        compatible: alloc::vec![String::from("virtio,mmio")],
+                        virtio: virtio_type(&mmio),
+                    },
+                    mmio,
+                    io_ports: Vec::new(),
+                    irq: None,
+                }
+            })
+            .collect();
+    }
+    nodes
+        .into_iter()
+        .map(|node| {
+            let virtio = node
+                .compatible
+                .iter()
+                .any(|c| c == "virtio,mmio")
+                .then(|| virtio_type(&node.regs))
+                .flatten();
+            Device {
+                name: node.name,
+                ids: DeviceIds::Dt {
+                    compatible: node.compatible,
+                    virtio,
+                },
+                mmio: node.regs,
+                io_ports: Vec::new(),
+                irq: node.irqs.first().copied(),
+            }
+        })
+        .collect()
+}
+
+/// Devices of this machine: PCI on x86_64, the device tree elsewhere.
+pub fn devices() -> Vec<Device> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        pci_devices()
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        dt_devices()
+    }
+}
+
+/// State of a bound driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriverState {
+    Running,
+    /// The driver process died; it is started again at `at_ms`.
+    Restarting {
+        at_ms: u64,
+    },
+    /// The driver kept dying and was given up on; its device stays
+    /// quiesced.
+    Failed,
+}
+
+/// A device bound to a driver.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub driver: String,
+    pub device: String,
+    /// Driver process; `None` for a driver built into the kernel.
+    pub pid: Option<ProcessId>,
+    pub state: DriverState,
+    /// Restarts since the driver last ran for [`recovery::STABLE_MS`].
+    pub restarts: u32,
+}
+
+/// A binding with what it takes to restart its driver.
+struct Bound {
+    binding: Binding,
+    manifest: DriverManifest,
+    device: Device,
+    started_ms: u64,
+    /// Device state to replay before a restart.
+    saved: recovery::SavedState,
+}
+
+static BINDINGS: Mutex<Vec<Bound>> = Mutex::new(Vec::new());
+
+/// Devices bound so far.
+pub fn bindings() -> Vec<Binding> {
+    BINDINGS.lock().iter().map(|b| b.binding.clone()).collect()
+}
+
+/// Bind each device of `devices` not bound yet to the first driver of
+/// `manifests` matching it, in dependency order, with `start`.
+fn bind(
+    manifests: &[DriverManifest],
+    devices: &[Device],
+    mut start: impl FnMut(&DriverManifest, &Device) -> Result<Option<ProcessId>, KernelError>,
+) {
+    let bound_drivers: Vec<String> = BINDINGS
+        .lock()
+        .iter()
+        .map(|b| b.binding.driver.clone())
+        .collect();
+    let (order, unmet) = bind_order(manifests, &bound_drivers);
+    for _i in unmet {
+        crate::println!(
+            "[DEVMGR] {}: requirements {:?} not met, not started",
+            manifests[_i].name,
+            manifests[_i].requires
+        );
+    }
+
+    for i in order {
+        let manifest = &manifests[i];
+        for device in devices.iter().filter(|device| manifest.matches(device)) {
+            if BINDINGS
+                .lock()
+                .iter()
+                .any(|b| b.binding.device == device.name)
+            {
+                continue;
+            }
+            // Saved before the driver touches the device.
+            let saved = recovery::save(device);
+            match start(manifest, device) {
+                Ok(pid) => {
+                    crate::println!("[DEVMGR] {} bound to {}", manifest.name, device.name);
+                    BINDINGS.lock().push(Bound {
+                        binding: Binding {
+                            driver: manifest.name.clone(),
+                            device: device.name.clone(),
+                            pid,
+                            state: DriverState::Running,
+                            restarts: 0,
+                        },
+                        manifest: manifest.clone(),
+                        device: device.clone(),
+                        started_ms: crate::arch::timer::get_timestamp_ms(),
+                        saved,
+                    });
+                }
+                Err(_e) => {
+                    crate::println!(
+                        "[DEVMGR] {} failed on {}: {:?}",
+                        manifest.name,
+                        device.name,
+                        _e
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Bind the drivers built into the kernel to their devices.
+///
+/// Runs during boot, after the PCI bus is enumerated.
+pub fn init() {
+    let manifests = builtin_manifests();
+    bind(&manifests, &devices(), |manifest, device| {
+        let builtin = BUILTIN
+            .iter()
+            .find(|builtin| builtin.name == manifest.name)
+            .ok_or(KernelError::NotFound {
+                resource: "built-in driver",
+                id: 0,
+            })?;
+        (builtin.attach)(device).map(|()| None)
+    });
+}
+
+/// Load every manifest in [`DRIVER_DIR`]. Files that fail to parse are
+/// reported and skipped; a missing directory yields no drivers.
+pub fn load_manifests() -> Vec<DriverManifest> {
+    let mut manifests = Vec::new();
+    let Ok(dir) = crate::fs::get_vfs().read().resolve_path(DRIVER_DIR) else {
+        return manifests;
+    };
+    let Ok(entries) = dir.readdir() else {
+        return manifests;
+    };
+    for entry in entries {
+        let Some(name) = entry.name.strip_suffix(UNIT_SUFFIX) else {
+            continue;
+        };
+        if entry.node_type != crate::fs::NodeType::File {
+            continue;
+        }
+        let path = format!("{}/{}", DRIVER_DIR, entry.name);
+        let parsed = crate::fs::read_file(&path).and_then(|data| {
+            let text = core::str::from_utf8(&data).map_err(|_| invalid("not UTF-8"))?;
+            parse_manifest(name, text)
+        });
+        match parsed {
+            Ok(manifest) if BUILTIN.iter().any(|b| b.name == manifest.name) => {
+                crate::println!("[DEVMGR] Skipping {}: driver is built in", path)
+            }
+            Ok(manifest) => manifests.push(manifest),
+            Err(_e) => crate::println!("[DEVMGR] Skipping {}: {:?}", path, _e),
+        }
+    }
+    manifests
+}
+
+/// Give driver process `pid` capabilities for the `resources` of `device`.
+fn grant(pid: ProcessId, device: &Device, resources: &[Resource]) -> Result<(), KernelError> {
+    use crate::cap::{manager::cap_manager, object::MemoryAttributes, ObjectRef, Rights};
+
+    let process =
+        crate::process::get_process(pid).ok_or(KernelError::ProcessNotFound { pid: pid.0 })?;
+    let mut objects = Vec::new();
+    for resource in resources {
+        match resource {
+            Resource::Mmio => {
+                objects.extend(device.mmio.iter().map(|&(base, size)| ObjectRef::Memory {
+                    base: base as usize,
+                    size: size as usize,
+                    attributes: MemoryAttributes::device(),
+                }))
+            }
+            Resource::IoPort => objects.extend(
+                device
+                    .io_ports
+                    .iter()
+                    .map(|&(base, size)| ObjectRef::IoPort { base, size }),
+            ),
+            Resource::Irq => objects.extend(device.irq.map(|irq| ObjectRef::Interrupt { irq })),
+        }
+    }
+
+    let cap_space = process.capability_space.lock();
+    for object in objects {
+        cap_manager()
+            .create_capability(object, Rights::READ | Rights::WRITE, &cap_space)
+            .map_err(|_| KernelError::ResourceExhausted {
+                resource: "capabilities",
+            })?;
+    }
+    Ok(())
+}
+
+/// Start a process of user-space driver `manifest` for `device`, holding
+/// capabilities for the device. `restarts` is passed on in
+/// `DEVICE_RESTARTS` so a restarted driver knows to recover.
+fn spawn(
+    manifest: &DriverManifest,
+    device: &Device,
+    restarts: u32,
+) -> Result<ProcessId, KernelError> {
+    let mut argv: Vec<&str> = alloc::vec![manifest.exec.as_str()];
+    argv.extend(manifest.args.iter().map(String::as_str));
+    let mut env = driver_env(device);
+    if restarts > 0 {
+        env.push(format!("DEVICE_RESTARTS={}", restarts));
+    }
+    let envp: Vec<&str> = env.iter().map(String::as_str).collect();
+
+    let pid = crate::userspace::load_user_program(&manifest.exec, &argv, &envp)?;
+    grant(pid, device, &manifest.capabilities)?;
+    Ok(pid)
+}
+
+/// Start the user-space drivers of [`DRIVER_DIR`] for the devices no driver
+/// is bound to yet.
+///
+/// Runs once the scheduler is up and the root file system is loaded.
+pub fn start_user_drivers() {
+    let manifests = load_manifests();
+    if manifests.is_empty() {
+        return;
+    }
+    bind(&manifests, &devices(), |manifest, device| {
+        spawn(manifest, device, 0).map(Some)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pci(vendor: u16, device: u16, class: u8, subclass: u8) -> Device {
+        Device {
+            name: String::from("pci-00:04.0"),
+            ids: DeviceIds::Pci {
+                location: PciLocation::new(0, 4, 0),
+                vendor,
+                device,
+                class,
+                subclass,
+            },
+            mmio: Vec::new(),
+            io_ports: alloc::vec![(0xc000, 0x40)],
+            irq: Some(11),
+        }
+    }
+
+    fn dt(compatible: &str, virtio: Option<u32>) -> Device {
+        Device {
+            name: String::from("virtio_mmio@a000000"),
+            ids: DeviceIds::Dt {
+                compatible: alloc::vec![String::from(compatible)],
+                virtio,
+            },
+            mmio: alloc::vec![(0xa00_0000, 0x200)],
+            io_ports: Vec::new(),
+            irq: Some(48),
+        }
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = parse_manifest(
+            "virtio-net",
+            r#"
+[driver]
+description = "Virtio network device"
+requires = ["virtio-blk"]
+capabilities = ["mmio", "irq"]
+
+[match]
+pci = ["1af4:1000", "8086:*"]
+pci_class = ["02:*"]
+compatible = ["virtio,mmio"]
+virtio = 1
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.exec, "/drivers/virtio-net");
+        assert_eq!(manifest.requires, ["virtio-blk"]);
+        assert_eq!(manifest.capabilities, [Resource::Mmio, Resource::Irq]);
+        assert_eq!(manifest.virtio, Some(1));
+        assert_eq!(
+            manifest.matches,
+            [
+                DeviceMatch::Pci {
+                    vendor: 0x1af4,
+                    device: Some(0x1000),
+                },
+                DeviceMatch::Pci {
+                    vendor: 0x8086,
+                    device: None,
+                },
+                DeviceMatch::PciClass {
+                    class: 2,
+                    subclass: None,
+                },
+                DeviceMatch::Compatible(String::from("virtio,mmio")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_errors() {
+        assert!(parse_manifest("x", "[driver]\ndescription = \"none\"\n").is_err());
+        assert!(parse_manifest("x", "[match]\npci = [\"1af4\"]\n").is_err());
+        assert!(parse_manifest("x", "[match]\npci_class = [\"100:00\"]\n").is_err());
+        assert!(parse_manifest(
+            "x",
+            "[driver]\ncapabilities = [\"dma\"]\n[match]\ncompatible = [\"a\"]\n"
+        )
+        .is_err());
+        assert!(parse_manifest("bad name", "[match]\ncompatible = [\"a\"]\n").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let blk = &builtin_manifests()[0];
+        assert!(blk.exec.is_empty());
+        assert!(blk.matches(&pci(0x1af4, 0x1001, 1, 0)));
+        assert!(!blk.matches(&pci(0x1af4, 0x1000, 2, 0)));
+        assert!(blk.matches(&dt("virtio,mmio", Some(2))));
+        assert!(!blk.matches(&dt("virtio,mmio", Some(1))));
+        assert!(!blk.matches(&dt("virtio,mmio", None)));
+        assert!(!blk.matches(&dt("arm,pl011", None)));
+
+        // Each virtio type binds only its own driver
+        let builtins = builtin_manifests();
+        let bound_by = |device: &Device| -> Vec<&str> {
+            builtins
+                .iter()
+                .filter(|m| m.matches(device))
+                .map(|m| m.name.as_str())
+                .collect()
+        };
+        assert_eq!(bound_by(&dt("virtio,mmio", Some(3))), ["virtio-console"]);
+        assert_eq!(bound_by(&dt("virtio,mmio", Some(9))), ["virtio-9p"]);
+        assert_eq!(bound_by(&pci(0x1af4, 0x1049, 0, 0)), ["virtio-9p"]);
+
+        let nic = parse_manifest("nic", "[match]\npci_class = [\"02:00\"]\n").unwrap();
+        assert!(nic.matches(&pci(0x8086, 0x100e, 2, 0)));
+        assert!(!nic.matches(&pci(0x8086, 0x100e, 2, 1)));
+    }
+
+    #[test]
+    fn test_bind_order() {
+        let manifest = |name: &str, requires: &[&str]| {
+            let mut manifest = parse_manifest(name, "[match]\ncompatible = [\"x\"]\n").unwrap();
+            manifest.requires = requires.iter().map(|&r| String::from(r)).collect();
+            manifest
+        };
+        let manifests = [
+            manifest("net", &["bus", "virtio-blk"]),
+            manifest("bus", &[]),
+            manifest("a", &["b"]),
+            manifest("b", &["a"]),
+            manifest("orphan", &["missing"]),
+        ];
+        let (order, unmet) = bind_order(&manifests, &[String::from("virtio-blk")]);
+        assert_eq!(order, [1, 0]);
+        assert_eq!(unmet, [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_driver_env() {
+        assert_eq!(
+            driver_env(&pci(0x1af4, 0x1000, 2, 0)),
+            [
+                "DEVICE=pci-00:04.0",
+                "DEVICE_IOPORTS=0xc000:0x40",
+                "DEVICE_IRQ=11"
+            ]
+        );
+        assert_eq!(
+            driver_env(&dt("virtio,mmio", Some(2))),
+            [
+                "DEVICE=virtio_mmio@a000000",
+                "DEVICE_MMIO=0xa000000:0x200",
+                "DEVICE_IRQ=48"
+            ]
+        );
+    }
+}
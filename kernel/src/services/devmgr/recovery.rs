@@ -0,0 +1,279 @@
+//! Driver crash recovery
+//!
+//! A user-space driver that dies leaves its device in the middle of
+//! whatever it was doing: DMA may still target the memory of the dead
+//! process, which is about to be freed, and a raised interrupt has nobody
+//! left to acknowledge it. So when a bound driver process exits, for any
+//! reason, [`driver_exited`] quiesces its device before the process's
+//! memory is released:
+//! - the interrupt is masked at the interrupt controller;
+//! - a PCI function loses bus mastering and has INTx disabled;
+//! - a virtio-mmio device is reset, which stops its virtqueues.
+//!
+//! [`poll`], run from the idle loop, then restarts the driver after a
+//! backoff. The device state saved when it was first bound (the PCI BARs,
+//! command register and interrupt line) is written back and the interrupt
+//! unmasked first, so the new process finds the device as the first one
+//! did. A driver that dies [`RESTART_LIMIT`] times without running for
+//! [`STABLE_MS`] in between is given up on and its device left quiesced.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use spin::Mutex;
+
+use super::{Device, DeviceIds, DriverState, BINDINGS};
+use crate::{drivers::pci, process::ProcessId};
+
+/// Restarts in a row before a driver is given up on.
+pub const RESTART_LIMIT: u32 = 5;
+
+/// A driver that ran this long before dying is restarted as if for the
+/// first time.
+pub const STABLE_MS: u64 = 30_000;
+
+/// Delay before the first restart, doubled on each further one.
+const RESTART_DELAY_MS: u64 = 100;
+
+/// Some driver is waiting to be restarted.
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Held while restarting drivers, so idle loops on several CPUs do not
+/// restart the same one.
+static RESTARTING: Mutex<()> = Mutex::new(());
+
+/// Device state replayed before a driver restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SavedState {
+    None,
+    Pci(pci::SavedHeader),
+}
+
+/// Save the state of `device` to replay on a driver restart.
+pub(super) fn save(device: &Device) -> SavedState {
+    match device.ids {
+        DeviceIds::Pci { location, .. } if pci::is_pci_initialized() => {
+            SavedState::Pci(pci::save_header(location))
+        }
+        _ => SavedState::None,
+    }
+}
+
+/// Mask or unmask `irq` at the interrupt controller.
+fn set_irq_masked(irq: u32, masked: bool) {
+    #[cfg(target_arch = "x86_64")]
+    let _ = match (u8::try_from(irq), masked) {
+        (Ok(irq), true) => crate::arch::x86_64::apic::mask_irq(irq),
+        (Ok(irq), false) => crate::arch::x86_64::apic::unmask_irq(irq),
+        (Err(_), _) => Ok(()),
+    };
+
+    #[cfg(target_arch = "aarch64")]
+    let _ = if masked {
+        crate::arch::aarch64::gic::disable_irq(irq)
+    } else {
+        crate::arch::aarch64::gic::enable_irq(irq)
+    };
+
+    #[cfg(target_arch = "riscv64")]
+    let _ = if masked {
+        crate::arch::riscv::plic::disable(irq)
+    } else {
+        crate::arch::riscv::plic::enable(irq)
+    };
+}
+
+/// Stop `device` from raising interrupts and doing DMA.
+fn quiesce(device: &Device) {
+    if let Some(irq) = device.irq {
+        set_irq_masked(irq, true);
+    }
+    match &device.ids {
+        DeviceIds::Pci { location, .. } if pci::is_pci_initialized() => pci::quiesce(*location),
+        DeviceIds::Dt {
+            virtio: Some(_), ..
+        } => {
+            if let Some(&(base, _)) = device.mmio.first() {
+                crate::drivers::virtio::mmio::VirtioMmioTransport::new(base as usize).reset();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Put `device` back in the state it was bound in.
+fn replay(device: &Device, saved: &SavedState) {
+    if let (DeviceIds::Pci { location, .. }, SavedState::Pci(header)) = (&device.ids, saved) {
+        pci::restore_header(*location, header);
+    }
+    if let Some(irq) = device.irq {
+        set_irq_masked(irq, false);
+    }
+}
+
+/// Delay before restart number `restarts + 1`.
+fn restart_delay_ms(restarts: u32) -> u64 {
+    RESTART_DELAY_MS << restarts.min(6)
+}
+
+/// What to do with a driver that died after `restarts` restarts and
+/// `ran_ms` of running.
+fn next_state(restarts: u32, ran_ms: u64, now_ms: u64) -> (DriverState, u32) {
+    let restarts = if ran_ms >= STABLE_MS { 0 } else { restarts };
+    if restarts >= RESTART_LIMIT {
+        (DriverState::Failed, restarts)
+    } else {
+        (
+            DriverState::Restarting {
+                at_ms: now_ms + restart_delay_ms(restarts),
+            },
+            restarts,
+        )
+    }
+}
+
+/// Process `pid` is exiting. If it is a driver, quiesce its device and
+/// schedule a restart.
+///
+/// Called while the process is torn down, before its memory is freed.
+pub fn driver_exited(pid: ProcessId) {
+    let mut bindings = BINDINGS.lock();
+    let Some(bound) = bindings
+        .iter_mut()
+        .find(|bound| bound.binding.pid == Some(pid))
+    else {
+        return;
+    };
+    quiesce(&bound.device);
+
+    let now = crate::arch::timer::get_timestamp_ms();
+    let (state, restarts) = next_state(
+        bound.binding.restarts,
+        now.saturating_sub(bound.started_ms),
+        now,
+    );
+    bound.binding.pid = None;
+    bound.binding.state = state;
+    bound.binding.restarts = restarts;
+    match state {
+        DriverState::Failed => crate::println!(
+            "[DEVMGR] {} on {} keeps failing, giving up",
+            bound.binding.driver,
+            bound.binding.device
+        ),
+        _ => {
+            crate::println!(
+                "[DEVMGR] {} on {} died, device quiesced",
+                bound.binding.driver,
+                bound.binding.device
+            );
+            PENDING.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Restart the drivers whose restart is due.
+///
+/// Called from the idle loop: starting a driver loads its binary.
+pub fn poll() {
+    if !PENDING.load(Ordering::Acquire) {
+        return;
+    }
+    let Some(_restarting) = RESTARTING.try_lock() else {
+        return;
+    };
+
+    let now = crate::arch::timer::get_timestamp_ms();
+    let due: Vec<usize> = BINDINGS
+        .lock()
+        .iter()
+        .enumerate()
+        .filter(|(_, bound)| {
+            matches!(bound.binding.state, DriverState::Restarting { at_ms } if at_ms <= now)
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    for i in due {
+        // Bindings are never removed, so the index stays valid.
+        let (manifest, device, saved, restarts) = {
+            let bindings = BINDINGS.lock();
+            let bound = &bindings[i];
+            (
+                bound.manifest.clone(),
+                bound.device.clone(),
+                bound.saved,
+                bound.binding.restarts + 1,
+            )
+        };
+        replay(&device, &saved);
+        let started = super::spawn(&manifest, &device, restarts);
+
+        let mut bindings = BINDINGS.lock();
+        let bound = &mut bindings[i];
+        bound.binding.restarts = restarts;
+        match started {
+            Ok(pid) => {
+                crate::println!(
+                    "[DEVMGR] {} restarted on {} (PID {})",
+                    manifest.name,
+                    device.name,
+                    pid.0
+                );
+                bound.binding.pid = Some(pid);
+                bound.binding.state = DriverState::Running;
+                bound.started_ms = now;
+            }
+            Err(_e) => {
+                crate::println!(
+                    "[DEVMGR] {} restart on {} failed: {:?}",
+                    manifest.name,
+                    device.name,
+                    _e
+                );
+                quiesce(&device);
+                let (state, _) = next_state(restarts, 0, now);
+                bound.binding.state = state;
+            }
+        }
+    }
+
+    let pending = BINDINGS
+        .lock()
+        .iter()
+        .any(|bound| matches!(bound.binding.state, DriverState::Restarting { .. }));
+    PENDING.store(pending, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff() {
+        assert_eq!(restart_delay_ms(0), 100);
+        assert_eq!(restart_delay_ms(3), 800);
+        assert_eq!(restart_delay_ms(40), restart_delay_ms(6));
+    }
+
+    #[test]
+    fn test_next_state() {
+        assert_eq!(
+            next_state(0, 10, 1000),
+            (DriverState::Restarting { at_ms: 1100 }, 0)
+        );
+        assert_eq!(
+            next_state(2, 10, 1000),
+            (DriverState::Restarting { at_ms: 1400 }, 2)
+        );
+        assert_eq!(
+            next_state(RESTART_LIMIT, 10, 1000),
+            (DriverState::Failed, RESTART_LIMIT)
+        );
+        // A driver that ran long enough starts over.
+        assert_eq!(
+            next_state(RESTART_LIMIT, STABLE_MS, 1000),
+            (DriverState::Restarting { at_ms: 1100 }, 0)
+        );
+    }
+}
@@ -1,15 +1,32 @@
 //! Driver Framework Implementation
 //!
 //! Provides driver registration, device enumeration, and driver-device binding.
+//!
+//! It is also the power management core for bound devices. A device may
+//! name a parent (a controller, a bridge, the disk under a partition) that
+//! must be powered while it is, so system suspend ([`suspend_devices`])
+//! suspends every device before its parent and [`resume_devices`] resumes
+//! parents first; a failed suspend resumes what was already suspended.
+//! [`restart_device`] runs the same sequence over one device and those
+//! below it, for drivers that need to reset their hardware. Between system
+//! sleeps, devices are runtime-suspended when the last reference taken
+//! with [`pm_get`] is dropped, no child is powered, and their driver's
+//! [`Driver::runtime_idle`] agrees; I/O through the framework holds such a
+//! reference.
+//!
+//! [`suspend_devices`]: DriverFramework::suspend_devices
+//! [`resume_devices`]: DriverFramework::resume_devices
+//! [`restart_device`]: DriverFramework::restart_device
+//! [`pm_get`]: DriverFramework::pm_get
 
 #![allow(clippy::unwrap_or_default)]
 
 use alloc::{boxed::Box, collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 use core::sync::atomic::{AtomicU64, Ordering};
 
-use spin::RwLock;
+use spin::{Mutex, RwLock};
 
-use crate::error::KernelError;
+use crate::{error::KernelError, sync::rcu::Rcu};
 
 /// Device event for hot-plug notifications.
 #[derive(Debug, Clone)]
@@ -78,6 +95,9 @@ pub struct DeviceInfo {
     pub io_ports: Vec<(u16, u16)>,       // (start, end)
     pub memory_regions: Vec<(u64, u64)>, // (start, size)
     pub status: DeviceStatus,
+    /// Device that must stay powered while this one is, if any.
+    pub parent: Option<u64>,
+    pub power: PowerState,
 }
 
 /// Device status
@@ -91,6 +111,17 @@ pub enum DeviceStatus {
     Removed,
 }
 
+/// Device power state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    /// Powered and serving I/O.
+    On,
+    /// Powered down while the system runs, until its next user.
+    RuntimeSuspended,
+    /// Powered down for a system sleep state.
+    Suspended,
+}
+
 /// Driver operations trait
 pub trait Driver: Send + Sync {
     /// Get driver name
@@ -111,11 +142,18 @@ pub trait Driver: Send + Sync {
     /// Detach from device
     fn detach(&mut self, device: &DeviceInfo) -> Result<(), KernelError>;
 
-    /// Suspend device
-    fn suspend(&mut self) -> Result<(), KernelError>;
+    /// Suspend device, after all of its children; it must not raise
+    /// interrupts or do DMA until resumed
+    fn suspend(&mut self, device: &DeviceInfo) -> Result<(), KernelError>;
 
-    /// Resume device
-    fn resume(&mut self) -> Result<(), KernelError>;
+    /// Resume device, before any of its children
+    fn resume(&mut self, device: &DeviceInfo) -> Result<(), KernelError>;
+
+    /// Whether a device nothing uses may be runtime-suspended now. Only
+    /// drivers that opt in are powered down while the system runs.
+    fn runtime_idle(&mut self, _device: &DeviceInfo) -> bool {
+        false
+    }
 
     /// Handle interrupt
     fn handle_interrupt(&mut self, irq: u8) -> Result<(), KernelError>;
@@ -168,17 +206,23 @@ pub struct DriverFramework {
     /// Discovered devices
     devices: RwLock<BTreeMap<u64, DeviceInfo>>,
 
-    /// Driver-device bindings
-    bindings: RwLock<BTreeMap<u64, String>>, // device_id -> driver_name
+    /// Driver-device bindings, read on every device I/O (RCU-protected)
+    bindings: Rcu<BTreeMap<u64, String>>, // device_id -> driver_name
 
     /// Next device ID
     next_device_id: AtomicU64,
 
-    /// IRQ handlers
-    irq_handlers: RwLock<BTreeMap<u8, Vec<String>>>, // IRQ -> driver names
+    /// IRQ handlers, read on every interrupt (RCU-protected)
+    irq_handlers: Rcu<BTreeMap<u8, Vec<String>>>, // IRQ -> driver names
 
     /// Event listeners for device hot-plug notifications
     event_listeners: RwLock<Vec<Arc<dyn DeviceEventListener>>>,
+
+    /// Runtime PM references held on each device
+    pm_usage: RwLock<BTreeMap<u64, u32>>,
+
+    /// Serializes power transitions
+    pm_lock: Mutex<()>,
 }
 
 impl DriverFramework {
@@ -188,10 +232,12 @@ impl DriverFramework {
             drivers: RwLock::new(BTreeMap::new()),
             buses: RwLock::new(BTreeMap::new()),
             devices: RwLock::new(BTreeMap::new()),
-            bindings: RwLock::new(BTreeMap::new()),
+            bindings: Rcu::new(BTreeMap::new()),
             next_device_id: AtomicU64::new(1),
-            irq_handlers: RwLock::new(BTreeMap::new()),
+            irq_handlers: Rcu::new(BTreeMap::new()),
             event_listeners: RwLock::new(Vec::new()),
+            pm_usage: RwLock::new(BTreeMap::new()),
+            pm_lock: Mutex::new(()),
         }
     }
 }
@@ -321,7 +367,8 @@ impl DriverFramework {
                 if driver.probe(&device).is_ok() {
                     // Attach driver
                     if driver.attach(&device).is_ok() {
-                        self.bindings.write().insert(device_id, driver_name.clone());
+                        self.bindings
+                            .update(|bindings| bindings.insert(device_id, driver_name.clone()));
 
                         // Update device status
                         if let Some(dev) = self.devices.write().get_mut(&device_id) {
@@ -331,11 +378,12 @@ impl DriverFramework {
 
                         // Register IRQ handler if device has IRQ
                         if let Some(irq) = device.irq {
-                            self.irq_handlers
-                                .write()
-                                .entry(irq)
-                                .or_insert_with(Vec::new)
-                                .push(driver_name.clone());
+                            self.irq_handlers.update(|handlers| {
+                                handlers
+                                    .entry(irq)
+                                    .or_insert_with(Vec::new)
+                                    .push(driver_name.clone())
+                            });
                         }
 
                         crate::println!(
@@ -407,7 +455,8 @@ impl DriverFramework {
                     };
 
                     if attach_ok {
-                        self.bindings.write().insert(device_id, driver_name.into());
+                        self.bindings
+                            .update(|bindings| bindings.insert(device_id, driver_name.into()));
 
                         if let Some(dev) = self.devices.write().get_mut(&device_id) {
                             dev.driver = Some(driver_name.into());
@@ -415,11 +464,12 @@ impl DriverFramework {
                         }
 
                         if let Some(irq) = device.irq {
-                            self.irq_handlers
-                                .write()
-                                .entry(irq)
-                                .or_insert_with(Vec::new)
-                                .push(driver_name.into());
+                            self.irq_handlers.update(|handlers| {
+                                handlers
+                                    .entry(irq)
+                                    .or_insert_with(Vec::new)
+                                    .push(driver_name.into())
+                            });
                         }
 
                         crate::println!(
@@ -437,7 +487,7 @@ impl DriverFramework {
 
     /// Unbind device from driver
     fn unbind_device(&self, device_id: u64) -> Result<(), KernelError> {
-        if let Some(driver_name) = self.bindings.write().remove(&device_id) {
+        if let Some(driver_name) = self.bindings.update(|bindings| bindings.remove(&device_id)) {
             let device =
                 self.devices
                     .read()
@@ -449,19 +499,26 @@ impl DriverFramework {
                     })?;
 
             if let Some(driver) = self.drivers.write().get_mut(&driver_name) {
+                // Drivers detach from powered devices only
+                if device.power != PowerState::On {
+                    driver.resume(&device)?;
+                }
                 driver.detach(&device)?;
             }
 
             if let Some(dev) = self.devices.write().get_mut(&device_id) {
                 dev.driver = None;
                 dev.status = DeviceStatus::Uninitialized;
+                dev.power = PowerState::On;
             }
 
             // Remove IRQ handler
             if let Some(irq) = device.irq {
-                if let Some(handlers) = self.irq_handlers.write().get_mut(&irq) {
-                    handlers.retain(|name| name != &driver_name);
-                }
+                self.irq_handlers.update(|handlers| {
+                    if let Some(handlers) = handlers.get_mut(&irq) {
+                        handlers.retain(|name| name != &driver_name);
+                    }
+                });
             }
 
             crate::println!(
@@ -616,7 +673,16 @@ impl DriverFramework {
         }
 
         // Remove device and fire event
-        let removed = self.devices.write().remove(&device_id);
+        let removed = {
+            let mut devices = self.devices.write();
+            for dev in devices.values_mut() {
+                if dev.parent == Some(device_id) {
+                    dev.parent = None;
+                }
+            }
+            devices.remove(&device_id)
+        };
+        self.pm_usage.write().remove(&device_id);
 
         if let Some(_dev) = removed {
             crate::println!(
@@ -678,13 +744,18 @@ impl DriverFramework {
                     id: device_id,
                 })?;
 
-        let mut drivers = self.drivers.write();
-        let driver = drivers.get_mut(&driver_name).ok_or(KernelError::NotFound {
-            resource: "driver",
-            id: 0,
-        })?;
-
-        driver.read(offset, buffer)
+        self.pm_get(device_id)?;
+        let result = self
+            .drivers
+            .write()
+            .get_mut(&driver_name)
+            .ok_or(KernelError::NotFound {
+                resource: "driver",
+                id: 0,
+            })
+            .and_then(|driver| driver.read(offset, buffer));
+        self.pm_put(device_id);
+        result
     }
 
     /// Write to a device through its bound driver.
@@ -704,13 +775,275 @@ impl DriverFramework {
                     id: device_id,
                 })?;
 
-        let mut drivers = self.drivers.write();
-        let driver = drivers.get_mut(&driver_name).ok_or(KernelError::NotFound {
-            resource: "driver",
-            id: 0,
+        self.pm_get(device_id)?;
+        let result = self
+            .drivers
+            .write()
+            .get_mut(&driver_name)
+            .ok_or(KernelError::NotFound {
+                resource: "driver",
+                id: 0,
+            })
+            .and_then(|driver| driver.write(offset, data));
+        self.pm_put(device_id);
+        result
+    }
+
+    /// Make `parent` the device `child` depends on: it is suspended after
+    /// `child` and resumed before it.
+    pub fn set_parent(&self, child: u64, parent: u64) -> Result<(), KernelError> {
+        let mut devices = self.devices.write();
+        if !devices.contains_key(&parent) {
+            return Err(KernelError::NotFound {
+                resource: "device",
+                id: parent,
+            });
+        }
+
+        // A device can't depend on itself, even through others
+        let mut ancestor = Some(parent);
+        while let Some(id) = ancestor {
+            if id == child {
+                return Err(KernelError::InvalidArgument {
+                    name: "parent",
+                    value: "depends on the device",
+                });
+            }
+            ancestor = devices.get(&id).and_then(|dev| dev.parent);
+        }
+
+        let dev = devices.get_mut(&child).ok_or(KernelError::NotFound {
+            resource: "device",
+            id: child,
+        })?;
+        dev.parent = Some(parent);
+        Ok(())
+    }
+
+    /// Bound devices in suspend order: each before its parent, and among
+    /// devices at the same depth the most recently added first. With
+    /// `root`, only that device and the devices below it.
+    fn pm_order(&self, root: Option<u64>) -> Vec<u64> {
+        let devices = self.devices.read();
+        let bindings = self.bindings.read();
+        let mut order: Vec<(usize, u64)> = bindings
+            .keys()
+            .filter_map(|&id| {
+                let mut depth = 0;
+                let mut below_root = root.is_none() || root == Some(id);
+                let mut ancestor = devices.get(&id).and_then(|dev| dev.parent);
+                while let Some(parent) = ancestor {
+                    depth += 1;
+                    below_root |= root == Some(parent);
+                    ancestor = devices.get(&parent).and_then(|dev| dev.parent);
+                }
+                below_root.then_some((depth, id))
+            })
+            .collect();
+        order.sort_unstable_by(|a, b| b.cmp(a));
+        order.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Move bound device `id` to `state` through its driver.
+    fn set_power(&self, id: u64, state: PowerState) -> Result<(), KernelError> {
+        let device = self.get_device(id).ok_or(KernelError::NotFound {
+            resource: "device",
+            id,
+        })?;
+        let driver_name = self.get_device_driver(id).ok_or(KernelError::NotFound {
+            resource: "device binding",
+            id,
         })?;
 
-        driver.write(offset, data)
+        {
+            let mut drivers = self.drivers.write();
+            let driver = drivers.get_mut(&driver_name).ok_or(KernelError::NotFound {
+                resource: "driver",
+                id: 0,
+            })?;
+            match state {
+                PowerState::On => driver.resume(&device)?,
+                PowerState::RuntimeSuspended | PowerState::Suspended => driver.suspend(&device)?,
+            }
+        }
+
+        if let Some(dev) = self.devices.write().get_mut(&id) {
+            dev.power = state;
+        }
+        let status = match state {
+            PowerState::Suspended => DeviceStatus::Suspended,
+            _ => DeviceStatus::Active,
+        };
+        self.update_device_status(id, status)
+    }
+
+    /// Suspend the powered devices under `root` (all with `None`) in
+    /// suspend order, returning them in that order. On a failure the
+    /// devices already suspended are resumed again.
+    fn suspend_tree(&self, root: Option<u64>) -> Result<Vec<u64>, KernelError> {
+        let mut suspended = Vec::new();
+        for id in self.pm_order(root) {
+            if self.get_device(id).map(|dev| dev.power) != Some(PowerState::On) {
+                continue;
+            }
+            if let Err(e) = self.set_power(id, PowerState::Suspended) {
+                crate::println!(
+                    "[DRIVER_FRAMEWORK] Suspend of device {} failed: {:?}",
+                    id,
+                    e
+                );
+                for &id in suspended.iter().rev() {
+                    let _ = self.set_power(id, PowerState::On);
+                }
+                return Err(e);
+            }
+            suspended.push(id);
+        }
+        Ok(suspended)
+    }
+
+    /// Suspend every bound device for a system sleep state, children before
+    /// their parents. Runtime-suspended devices stay as they are.
+    ///
+    /// If a driver fails, the devices suspended so far are resumed and its
+    /// error returned, so a failed system suspend leaves every device
+    /// running.
+    pub fn suspend_devices(&self) -> Result<(), KernelError> {
+        let _pm = self.pm_lock.lock();
+        self.suspend_tree(None).map(drop)
+    }
+
+    /// Resume the devices [`suspend_devices`](Self::suspend_devices)
+    /// suspended, parents before their children.
+    ///
+    /// A device that fails to resume stays suspended and the others are
+    /// still resumed; the first error is returned.
+    pub fn resume_devices(&self) -> Result<(), KernelError> {
+        let _pm = self.pm_lock.lock();
+        let mut result = Ok(());
+        for id in self.pm_order(None).into_iter().rev() {
+            if self.get_device(id).map(|dev| dev.power) != Some(PowerState::Suspended) {
+                continue;
+            }
+            if let Err(e) = self.set_power(id, PowerState::On) {
+                crate::println!("[DRIVER_FRAMEWORK] Resume of device {} failed: {:?}", id, e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Suspend a device and the devices below it, children first, then
+    /// resume them, parents first, e.g. to reset the device's hardware.
+    pub fn restart_device(&self, device_id: u64) -> Result<(), KernelError> {
+        let _pm = self.pm_lock.lock();
+        let suspended = self.suspend_tree(Some(device_id))?;
+        for &id in suspended.iter().rev() {
+            self.set_power(id, PowerState::On)?;
+        }
+        Ok(())
+    }
+
+    /// Take a runtime PM reference on a device, resuming it first (and
+    /// its parents before it) if it was runtime-suspended.
+    ///
+    /// Fails on a device suspended for system sleep.
+    pub fn pm_get(&self, device_id: u64) -> Result<(), KernelError> {
+        let _pm = self.pm_lock.lock();
+        self.runtime_resume(device_id)?;
+        *self.pm_usage.write().entry(device_id).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// Drop a reference taken with [`pm_get`](Self::pm_get).
+    ///
+    /// When the last one goes, the device is runtime-suspended if it is
+    /// [idle](Self::runtime_idle), and then its parent gets the same
+    /// chance.
+    pub fn pm_put(&self, device_id: u64) {
+        let _pm = self.pm_lock.lock();
+        let unused = match self.pm_usage.write().get_mut(&device_id) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                *count == 0
+            }
+            _ => false,
+        };
+
+        let mut next = unused.then_some(device_id);
+        while let Some(id) = next.take() {
+            if !self.runtime_idle(id) {
+                break;
+            }
+            if let Err(_e) = self.set_power(id, PowerState::RuntimeSuspended) {
+                crate::println!(
+                    "[DRIVER_FRAMEWORK] Runtime suspend of device {} failed: {:?}",
+                    id,
+                    _e
+                );
+                break;
+            }
+            next = self.get_device(id).and_then(|dev| dev.parent);
+        }
+    }
+
+    /// Power a device up for use, resuming runtime-suspended parents first.
+    fn runtime_resume(&self, id: u64) -> Result<(), KernelError> {
+        let device = self.get_device(id).ok_or(KernelError::NotFound {
+            resource: "device",
+            id,
+        })?;
+        match device.power {
+            PowerState::On => Ok(()),
+            PowerState::Suspended => Err(KernelError::InvalidState {
+                expected: "powered",
+                actual: "suspended",
+            }),
+            PowerState::RuntimeSuspended => {
+                if let Some(parent) = device.parent {
+                    self.runtime_resume(parent)?;
+                }
+                self.set_power(id, PowerState::On)
+            }
+        }
+    }
+
+    /// Whether a device may be runtime-suspended now: it is bound and
+    /// powered, nobody holds a reference, none of its bound children is
+    /// powered, and its driver agrees.
+    fn runtime_idle(&self, id: u64) -> bool {
+        if self
+            .pm_usage
+            .read()
+            .get(&id)
+            .is_some_and(|&count| count > 0)
+        {
+            return false;
+        }
+
+        let device = {
+            let devices = self.devices.read();
+            let Some(device) = devices.get(&id) else {
+                return false;
+            };
+            let busy_child = devices.values().any(|dev| {
+                dev.parent == Some(id) && dev.driver.is_some() && dev.power == PowerState::On
+            });
+            if device.power != PowerState::On || busy_child {
+                return false;
+            }
+            device.clone()
+        };
+
+        let Some(driver_name) = self.get_device_driver(id) else {
+            return false;
+        };
+        self.drivers
+            .write()
+            .get_mut(&driver_name)
+            .is_some_and(|driver| driver.runtime_idle(&device))
     }
 
     /// Get statistics
@@ -719,6 +1052,7 @@ impl DriverFramework {
         let mut active = 0;
         let mut failed = 0;
         let mut suspended = 0;
+        let mut runtime_suspended = 0;
 
         for device in devices.values() {
             match device.status {
@@ -727,6 +1061,9 @@ impl DriverFramework {
                 DeviceStatus::Suspended => suspended += 1,
                 _ => {}
             }
+            if device.power == PowerState::RuntimeSuspended {
+                runtime_suspended += 1;
+            }
         }
 
         DriverFrameworkStats {
@@ -737,6 +1074,7 @@ impl DriverFramework {
             active_devices: active,
             failed_devices: failed,
             suspended_devices: suspended,
+            runtime_suspended_devices: runtime_suspended,
         }
     }
 }
@@ -751,6 +1089,7 @@ pub struct DriverFrameworkStats {
     pub active_devices: usize,
     pub failed_devices: usize,
     pub suspended_devices: usize,
+    pub runtime_suspended_devices: usize,
 }
 
 /// Global driver framework using OnceLock for safe initialization.
@@ -787,3 +1126,206 @@ pub fn get_driver_framework() -> &'static DriverFramework {
         .get()
         .expect("Driver framework not initialized: init() was not called")
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, vec};
+
+    use super::*;
+
+    /// Binds every device on the `test` bus and logs power callbacks.
+    struct PmDriver {
+        log: Arc<Mutex<Vec<String>>>,
+        fail_suspend: Option<&'static str>,
+        idle: bool,
+    }
+
+    impl Driver for PmDriver {
+        fn name(&self) -> &str {
+            "pm"
+        }
+
+        fn supported_classes(&self) -> Vec<DeviceClass> {
+            vec![DeviceClass::Other]
+        }
+
+        fn supports_device(&self, device: &DeviceInfo) -> bool {
+            device.bus == "test"
+        }
+
+        fn probe(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+            Ok(())
+        }
+
+        fn attach(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+            Ok(())
+        }
+
+        fn detach(&mut self, _device: &DeviceInfo) -> Result<(), KernelError> {
+            Ok(())
+        }
+
+        fn suspend(&mut self, device: &DeviceInfo) -> Result<(), KernelError> {
+            if self.fail_suspend == Some(device.name.as_str()) {
+                return Err(KernelError::HardwareError {
+                    device: "test",
+                    code: 1,
+                });
+            }
+            self.log.lock().push(format!("suspend {}", device.name));
+            Ok(())
+        }
+
+        fn resume(&mut self, device: &DeviceInfo) -> Result<(), KernelError> {
+            self.log.lock().push(format!("resume {}", device.name));
+            Ok(())
+        }
+
+        fn runtime_idle(&mut self, _device: &DeviceInfo) -> bool {
+            self.idle
+        }
+
+        fn handle_interrupt(&mut self, _irq: u8) -> Result<(), KernelError> {
+            Ok(())
+        }
+
+        fn read(&mut self, _offset: u64, _buffer: &mut [u8]) -> Result<usize, KernelError> {
+            Ok(0)
+        }
+
+        fn write(&mut self, _offset: u64, data: &[u8]) -> Result<usize, KernelError> {
+            Ok(data.len())
+        }
+
+        fn ioctl(&mut self, _cmd: u32, _arg: u64) -> Result<u64, KernelError> {
+            Ok(0)
+        }
+    }
+
+    fn framework(
+        fail_suspend: Option<&'static str>,
+        idle: bool,
+    ) -> (DriverFramework, Arc<Mutex<Vec<String>>>) {
+        let framework = DriverFramework::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        framework
+            .register_driver(Box::new(PmDriver {
+                log: log.clone(),
+                fail_suspend,
+                idle,
+            }))
+            .unwrap();
+        (framework, log)
+    }
+
+    fn add(framework: &DriverFramework, name: &str, parent: Option<u64>) -> u64 {
+        framework
+            .add_device(DeviceInfo {
+                id: 0,
+                name: String::from(name),
+                class: DeviceClass::Other,
+                device_id: None,
+                driver: None,
+                bus: String::from("test"),
+                address: 0,
+                irq: None,
+                dma_channels: Vec::new(),
+                io_ports: Vec::new(),
+                memory_regions: Vec::new(),
+                status: DeviceStatus::Uninitialized,
+                parent,
+                power: PowerState::On,
+            })
+            .unwrap()
+    }
+
+    fn power(framework: &DriverFramework, id: u64) -> PowerState {
+        framework.get_device(id).unwrap().power
+    }
+
+    #[test]
+    fn test_suspend_children_before_parents() {
+        let (framework, log) = framework(None, false);
+        let disk = add(&framework, "disk", None);
+        let part = add(&framework, "part", Some(disk));
+        let bridge = add(&framework, "bridge", None);
+        let nic = add(&framework, "nic", None);
+        framework.set_parent(nic, bridge).unwrap();
+
+        framework.suspend_devices().unwrap();
+        assert_eq!(
+            *log.lock(),
+            [
+                "suspend nic",
+                "suspend part",
+                "suspend bridge",
+                "suspend disk"
+            ]
+        );
+        assert_eq!(power(&framework, part), PowerState::Suspended);
+        assert_eq!(
+            framework.get_device(disk).unwrap().status,
+            DeviceStatus::Suspended
+        );
+
+        log.lock().clear();
+        framework.resume_devices().unwrap();
+        assert_eq!(
+            *log.lock(),
+            ["resume disk", "resume bridge", "resume part", "resume nic"]
+        );
+        assert_eq!(framework.get_statistics().active_devices, 4);
+
+        log.lock().clear();
+        framework.restart_device(disk).unwrap();
+        assert_eq!(
+            *log.lock(),
+            ["suspend part", "suspend disk", "resume disk", "resume part"]
+        );
+
+        assert!(framework.set_parent(disk, part).is_err());
+    }
+
+    #[test]
+    fn test_failed_suspend_resumes_devices() {
+        let (framework, log) = framework(Some("disk"), false);
+        let disk = add(&framework, "disk", None);
+        let part = add(&framework, "part", Some(disk));
+
+        assert!(framework.suspend_devices().is_err());
+        assert_eq!(*log.lock(), ["suspend part", "resume part"]);
+        assert_eq!(power(&framework, disk), PowerState::On);
+        assert_eq!(power(&framework, part), PowerState::On);
+        assert_eq!(framework.get_statistics().suspended_devices, 0);
+    }
+
+    #[test]
+    fn test_runtime_pm() {
+        let (framework, log) = framework(None, true);
+        let disk = add(&framework, "disk", None);
+        let part = add(&framework, "part", Some(disk));
+
+        framework.pm_get(part).unwrap();
+        framework.pm_get(disk).unwrap();
+        framework.pm_put(part);
+        // The disk is still in use
+        assert_eq!(*log.lock(), ["suspend part"]);
+        framework.pm_put(disk);
+        assert_eq!(*log.lock(), ["suspend part", "suspend disk"]);
+        assert_eq!(framework.get_statistics().runtime_suspended_devices, 2);
+
+        log.lock().clear();
+        assert_eq!(framework.write_device(part, 0, &[0; 4]), Ok(4));
+        assert_eq!(
+            *log.lock(),
+            ["resume disk", "resume part", "suspend part", "suspend disk"]
+        );
+
+        // Runtime-suspended devices sleep through a system suspend
+        log.lock().clear();
+        framework.suspend_devices().unwrap();
+        framework.resume_devices().unwrap();
+        assert!(log.lock().is_empty());
+        assert_eq!(power(&framework, part), PowerState::RuntimeSuspended);
+    }
+}
@@ -2,14 +2,20 @@
 //!
 //! The init process (PID 1) that starts all system services and manages the
 //! system lifecycle.
+//!
+//! Services come from the unit files in `/etc/init` (see
+//! [`unit_file`](super::unit_file)) and start in dependency order.
 
 #![allow(clippy::if_same_then_else)]
 
+#[cfg(feature = "desktop")]
+use alloc::format;
 use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
 use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use spin::RwLock;
 
+use super::process_server::ResourceLimits;
 use crate::{error::KernelError, process::ProcessId};
 
 /// Service state
@@ -59,6 +65,7 @@ pub struct ServiceDefinition {
     pub dependencies: Vec<(String, DependencyType)>,
     pub start_level: u32,          // 0-99, lower starts first
     pub stop_timeout: Option<u32>, // Seconds to wait before SIGKILL (default: 10)
+    pub limits: ResourceLimits,
 }
 
 /// Service runtime information
@@ -135,8 +142,8 @@ impl InitSystem {
     pub fn initialize(&self) -> Result<(), KernelError> {
         crate::println!("[INIT] Initializing init system...");
 
-        // Register core system services
-        self.register_core_services()?;
+        // Register the services defined in /etc/init
+        self.register_unit_services()?;
 
         // Compute service start order
         self.compute_start_order()?;
@@ -219,6 +226,7 @@ impl InitSystem {
                 args
             },
             service.definition.environment.clone(),
+            service.definition.limits.clone(),
         )?;
 
         service.pid = Some(pid);
@@ -410,6 +418,13 @@ impl InitSystem {
                 } else if service.restart_count >= service.definition.max_restarts {
                     service.state = ServiceState::Failed;
                     service.last_error = Some(String::from("Max restart attempts exceeded"));
+                    #[cfg(feature = "desktop")]
+                    crate::desktop::notification::notify(
+                        &format!("Service {} failed", service.definition.name),
+                        "Max restart attempts exceeded",
+                        crate::desktop::notification::NotificationUrgency::Critical,
+                        "init",
+                    );
                 }
 
                 break;
@@ -449,207 +464,17 @@ impl InitSystem {
         self.switch_runlevel(Runlevel::Reboot)?;
 
         crate::println!("[INIT] System rebooting...");
-
-        // Architecture-specific reboot
-        #[cfg(target_arch = "x86_64")]
-        {
-            // Pulse the keyboard controller reset line (port 0xFE to 0x64)
-            // SAFETY: Writing 0xFE to keyboard controller port 0x64 triggers a system
-            // reset.
-            unsafe {
-                use x86_64::instructions::port::Port;
-                let mut port: Port<u8> = Port::new(0x64);
-                port.write(0xFE);
-            }
-        }
-
-        #[cfg(target_arch = "aarch64")]
-        {
-            // PSCI SYSTEM_RESET (function ID 0x84000009)
-            // SAFETY: Invoking PSCI SYSTEM_RESET via HVC; does not return on success.
-            unsafe {
-                core::arch::asm!("ldr x0, =0x84000009", "hvc #0", options(nomem, nostack));
-            }
-        }
-
-        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
-        {
-            // SBI system reset (SRST extension, function 0)
-            // SAFETY: Invoking SBI SRST ecall to reset the system; does not return on
-            // success.
-            unsafe {
-                core::arch::asm!(
-                    "li a7, 0x53525354", // SRST extension ID
-                    "li a6, 0",          // Function 0: system_reset
-                    "li a0, 0",          // Reset type: shutdown
-                    "li a1, 0",          // Reason: no reason
-                    "ecall",
-                    options(nomem, nostack)
-                );
-            }
-        }
-
-        Ok(())
+        crate::power::system::reboot()
     }
 
     // Helper functions
 
-    fn register_core_services(&self) -> Result<(), KernelError> {
-        // Register console service
-        self.register_service(ServiceDefinition {
-            name: String::from("console"),
-            description: String::from("System Console"),
-            command: String::from("/sbin/getty"),
-            arguments: vec![String::from("tty0")],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::Always,
-            restart_delay_ms: 1000,
-            max_restarts: 5,
-            timeout_ms: 5000,
-            dependencies: vec![],
-            start_level: 10,
-            stop_timeout: None,
-        })?;
-
-        // Register logger service
-        self.register_service(ServiceDefinition {
-            name: String::from("logger"),
-            description: String::from("System Logger"),
-            command: String::from("/sbin/syslogd"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::Always,
-            restart_delay_ms: 1000,
-            max_restarts: 5,
-            timeout_ms: 5000,
-            dependencies: vec![],
-            start_level: 5,
-            stop_timeout: None,
-        })?;
-
-        // Register device manager
-        self.register_service(ServiceDefinition {
-            name: String::from("devmgr"),
-            description: String::from("Device Manager"),
-            command: String::from("/sbin/devmgr"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::OnFailure,
-            restart_delay_ms: 2000,
-            max_restarts: 3,
-            timeout_ms: 10000,
-            dependencies: vec![(String::from("logger"), DependencyType::After)],
-            start_level: 15,
-            stop_timeout: None,
-        })?;
-
-        // Register network service
-        self.register_service(ServiceDefinition {
-            name: String::from("network"),
-            description: String::from("Network Service"),
-            command: String::from("/sbin/netd"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::OnFailure,
-            restart_delay_ms: 3000,
-            max_restarts: 3,
-            timeout_ms: 30000,
-            dependencies: vec![
-                (String::from("devmgr"), DependencyType::Requires),
-                (String::from("logger"), DependencyType::After),
-            ],
-            start_level: 20,
-            stop_timeout: None,
-        })?;
-
-        // Register firewall service
-        self.register_service(ServiceDefinition {
-            name: String::from("firewall"),
-            description: String::from("Packet Filter Firewall"),
-            command: String::from("/sbin/fwd"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::OnFailure,
-            restart_delay_ms: 3000,
-            max_restarts: 3,
-            timeout_ms: 30000,
-            dependencies: vec![(String::from("network"), DependencyType::After)],
-            start_level: 18,
-            stop_timeout: None,
-        })?;
-
-        // Register DNS resolver service
-        self.register_service(ServiceDefinition {
-            name: String::from("dns-resolver"),
-            description: String::from("DNS Resolver"),
-            command: String::from("/sbin/resolved"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::OnFailure,
-            restart_delay_ms: 3000,
-            max_restarts: 3,
-            timeout_ms: 30000,
-            dependencies: vec![(String::from("network"), DependencyType::Requires)],
-            start_level: 22,
-            stop_timeout: None,
-        })?;
-
-        // Register NTP time sync service
-        self.register_service(ServiceDefinition {
-            name: String::from("ntp"),
-            description: String::from("NTP Time Sync"),
-            command: String::from("/sbin/ntpd"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::OnFailure,
-            restart_delay_ms: 3000,
-            max_restarts: 3,
-            timeout_ms: 30000,
-            dependencies: vec![(String::from("network"), DependencyType::After)],
-            start_level: 25,
-            stop_timeout: None,
-        })?;
-
-        // Register SSH server service
-        self.register_service(ServiceDefinition {
-            name: String::from("ssh"),
-            description: String::from("SSH Server"),
-            command: String::from("/sbin/sshd"),
-            arguments: vec![],
-            environment: vec![],
-            working_directory: String::from("/"),
-            user: 0,
-            group: 0,
-            restart_policy: RestartPolicy::OnFailure,
-            restart_delay_ms: 3000,
-            max_restarts: 3,
-            timeout_ms: 30000,
-            dependencies: vec![(String::from("network"), DependencyType::After)],
-            start_level: 30,
-            stop_timeout: None,
-        })?;
-
+    /// Register the services defined in the unit files (see
+    /// [`unit_file`](super::unit_file)).
+    fn register_unit_services(&self) -> Result<(), KernelError> {
+        for definition in super::unit_file::load_units() {
+            self.register_service(definition)?;
+        }
         Ok(())
     }
 
@@ -8,15 +8,22 @@ pub mod cni;
 pub mod cri;
 pub mod csi;
 pub mod desktop_ipc;
+pub mod devmgr;
 pub mod driver_framework;
 pub mod init_system;
 pub mod lb;
+pub mod locale;
 pub mod mesh;
+#[cfg(feature = "desktop")]
 pub mod notification_ipc;
+pub mod power_ipc;
 pub mod print;
 pub mod process_server;
+#[cfg(feature = "desktop")]
+pub mod settings_ipc;
 pub mod shell;
 pub mod shell_utils;
+pub mod unit_file;
 
 pub use driver_framework::DriverFramework;
 pub use init_system::InitSystem;
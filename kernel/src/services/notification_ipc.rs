@@ -4,15 +4,27 @@
 //! applications send notification messages to the well-known notification
 //! endpoint, and this service dispatches them to the desktop notification
 //! manager for rendering as toast popups.
+//!
+//! Requests use the byte layout of [`NotificationMessage::encode`]. A sender
+//! that names a reply endpoint receives a small message with opcode
+//! [`NOTIFICATION_ACTION_OPCODE`] (ID in data register 0, action index in
+//! data register 1) when the user picks an action, and one with
+//! [`NOTIFICATION_CLOSED_OPCODE`] (ID, close reason) when the toast goes
+//! away. Senders without an endpoint, such as `notify-send`, poll with
+//! `Status` instead.
 
 #![allow(dead_code)]
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use core::sync::atomic::{AtomicBool, Ordering};
 
 use crate::{
-    desktop::notification::{self, NotificationUrgency},
+    desktop::notification::{
+        self, NotificationAction, NotificationEvent, NotificationRequest, NotificationStatus,
+        NotificationUrgency,
+    },
     error::KernelError,
+    ipc::{message::SmallMessage, EndpointId, Message},
     services::desktop_ipc::DESKTOP_NOTIFICATION_ENDPOINT,
 };
 
@@ -32,6 +44,8 @@ pub enum NotificationMessageType {
     DismissAll = 2,
     /// Query the count of active notifications.
     GetActive = 3,
+    /// Query what became of a notification (returns a `STATUS_*` code).
+    Status = 4,
 }
 
 impl NotificationMessageType {
@@ -42,13 +56,36 @@ impl NotificationMessageType {
             1 => Some(Self::Dismiss),
             2 => Some(Self::DismissAll),
             3 => Some(Self::GetActive),
+            4 => Some(Self::Status),
             _ => None,
         }
     }
 }
 
+/// Opcode of the event sent when the user invokes an action.
+pub const NOTIFICATION_ACTION_OPCODE: u32 = 0x4E70;
+
+/// Opcode of the event sent when a notification is removed.
+pub const NOTIFICATION_CLOSED_OPCODE: u32 = 0x4E71;
+
+/// `Status` reply: the notification is still shown.
+pub const STATUS_ACTIVE: u32 = 0;
+
+/// `Status` reply for action `n` is `STATUS_ACTION_BASE + n`; replies below
+/// it are [`notification::CloseReason`] values.
+pub const STATUS_ACTION_BASE: u32 = 0x100;
+
+/// Timeout value on the wire meaning "use the urgency default".
+pub const TIMEOUT_DEFAULT: u32 = u32::MAX;
+
+/// Largest encoded message accepted.
+pub const MAX_MESSAGE_SIZE: usize = 4096;
+
+/// Size of the fixed part of an encoded message.
+const HEADER_SIZE: usize = 18;
+
 /// A notification IPC message sent from user-space to the notification service.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NotificationMessage {
     /// The type of operation requested.
     pub msg_type: NotificationMessageType,
@@ -60,58 +97,199 @@ pub struct NotificationMessage {
     pub urgency: u8,
     /// Name of the sending application (used by `Notify`).
     pub app_name: String,
-    /// Notification ID (used by `Dismiss`).
+    /// Image path or icon name (used by `Notify`).
+    pub icon: Option<String>,
+    /// Display time in milliseconds, 0 for no timeout (used by `Notify`).
+    pub timeout_ms: Option<u32>,
+    /// Action buttons (used by `Notify`).
+    pub actions: Vec<NotificationAction>,
+    /// Endpoint that receives action and close events (used by `Notify`).
+    pub reply_endpoint: Option<EndpointId>,
+    /// Notification ID (used by `Dismiss` and `Status`).
     pub notification_id: u32,
 }
 
 impl NotificationMessage {
+    /// A message of `msg_type` with every field empty.
+    fn empty(msg_type: NotificationMessageType) -> Self {
+        Self {
+            msg_type,
+            summary: String::new(),
+            body: String::new(),
+            urgency: 1,
+            app_name: String::new(),
+            icon: None,
+            timeout_ms: None,
+            actions: Vec::new(),
+            reply_endpoint: None,
+            notification_id: 0,
+        }
+    }
+
     /// Create a `Notify` message.
     pub fn new_notify(summary: &str, body: &str, urgency: u8, app_name: &str) -> Self {
         Self {
-            msg_type: NotificationMessageType::Notify,
             summary: String::from(summary),
             body: String::from(body),
             urgency,
             app_name: String::from(app_name),
-            notification_id: 0,
+            ..Self::empty(NotificationMessageType::Notify)
         }
     }
 
     /// Create a `Dismiss` message for a specific notification.
     pub fn new_dismiss(id: u32) -> Self {
         Self {
-            msg_type: NotificationMessageType::Dismiss,
-            summary: String::new(),
-            body: String::new(),
-            urgency: 1,
-            app_name: String::new(),
             notification_id: id,
+            ..Self::empty(NotificationMessageType::Dismiss)
         }
     }
 
     /// Create a `DismissAll` message.
     pub fn new_dismiss_all() -> Self {
-        Self {
-            msg_type: NotificationMessageType::DismissAll,
-            summary: String::new(),
-            body: String::new(),
-            urgency: 1,
-            app_name: String::new(),
-            notification_id: 0,
-        }
+        Self::empty(NotificationMessageType::DismissAll)
     }
 
     /// Create a `GetActive` query message.
     pub fn new_get_active() -> Self {
+        Self::empty(NotificationMessageType::GetActive)
+    }
+
+    /// Create a `Status` query for a specific notification.
+    pub fn new_status(id: u32) -> Self {
         Self {
-            msg_type: NotificationMessageType::GetActive,
-            summary: String::new(),
-            body: String::new(),
-            urgency: 1,
-            app_name: String::new(),
-            notification_id: 0,
+            notification_id: id,
+            ..Self::empty(NotificationMessageType::Status)
+        }
+    }
+
+    /// Encode for the wire. All integers are little-endian:
+    ///
+    /// ```text
+    /// 0       u8    message type
+    /// 1       u8    urgency
+    /// 2..6    u32   notification ID
+    /// 6..10   u32   timeout in ms (TIMEOUT_DEFAULT = urgency default)
+    /// 10..18  u64   reply endpoint (0 = none)
+    /// 18..          app name, summary, body, icon (empty = none)
+    ///               u8 action count, then key and label of each action
+    /// ```
+    ///
+    /// Strings are a u16 byte length followed by UTF-8.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_SIZE + self.summary.len() + self.body.len() + 16);
+        buf.push(self.msg_type as u8);
+        buf.push(self.urgency);
+        buf.extend_from_slice(&self.notification_id.to_le_bytes());
+        buf.extend_from_slice(&self.timeout_ms.unwrap_or(TIMEOUT_DEFAULT).to_le_bytes());
+        buf.extend_from_slice(&self.reply_endpoint.unwrap_or(0).to_le_bytes());
+        for text in [
+            &self.app_name,
+            &self.summary,
+            &self.body,
+            self.icon.as_ref().unwrap_or(&String::new()),
+        ] {
+            put_string(&mut buf, text);
+        }
+        let count = self.actions.len().min(notification::MAX_ACTIONS);
+        buf.push(count as u8);
+        for action in &self.actions[..count] {
+            put_string(&mut buf, &action.key);
+            put_string(&mut buf, &action.label);
         }
+        buf
     }
+
+    /// Decode a message produced by [`NotificationMessage::encode`].
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        if buf.len() < HEADER_SIZE || buf.len() > MAX_MESSAGE_SIZE {
+            return None;
+        }
+        let msg_type = NotificationMessageType::from_u8(buf[0])?;
+        let notification_id = u32::from_le_bytes(buf[2..6].try_into().ok()?);
+        let timeout = u32::from_le_bytes(buf[6..10].try_into().ok()?);
+        let endpoint = u64::from_le_bytes(buf[10..18].try_into().ok()?);
+
+        let mut rest = &buf[HEADER_SIZE..];
+        let app_name = take_string(&mut rest)?;
+        let summary = take_string(&mut rest)?;
+        let body = take_string(&mut rest)?;
+        let icon = take_string(&mut rest)?;
+        let (&count, tail) = rest.split_first()?;
+        rest = tail;
+        if count as usize > notification::MAX_ACTIONS {
+            return None;
+        }
+        let mut actions = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let key = take_string(&mut rest)?;
+            let label = take_string(&mut rest)?;
+            actions.push(NotificationAction { key, label });
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            msg_type,
+            summary,
+            body,
+            urgency: buf[1],
+            app_name,
+            icon: (!icon.is_empty()).then_some(icon),
+            timeout_ms: (timeout != TIMEOUT_DEFAULT).then_some(timeout),
+            actions,
+            reply_endpoint: (endpoint != 0).then_some(endpoint),
+            notification_id,
+        })
+    }
+}
+
+/// Append a length-prefixed string, truncated to what a u16 length holds.
+fn put_string(buf: &mut Vec<u8>, text: &str) {
+    let mut len = text.len().min(u16::MAX as usize);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    buf.extend_from_slice(&(len as u16).to_le_bytes());
+    buf.extend_from_slice(&text.as_bytes()[..len]);
+}
+
+/// Split a length-prefixed string off the front of `buf`.
+fn take_string(buf: &mut &[u8]) -> Option<String> {
+    let len = u16::from_le_bytes(buf.get(..2)?.try_into().ok()?) as usize;
+    let text = core::str::from_utf8(buf.get(2..2 + len)?).ok()?;
+    *buf = &buf[2 + len..];
+    Some(String::from(text))
+}
+
+/// The reply to a `Status` query for `status`.
+pub fn encode_status(status: NotificationStatus) -> u32 {
+    match status {
+        NotificationStatus::Active => STATUS_ACTIVE,
+        NotificationStatus::ActionInvoked(action) => STATUS_ACTION_BASE + action as u32,
+        NotificationStatus::Closed(reason) => reason as u32,
+    }
+}
+
+/// Build the message pushed to a reply endpoint for `event`.
+pub fn encode_event(event: NotificationEvent) -> SmallMessage {
+    let (opcode, value) = match event {
+        NotificationEvent::ActionInvoked { action, .. } => {
+            (NOTIFICATION_ACTION_OPCODE, action as u64)
+        }
+        NotificationEvent::Closed { reason, .. } => (NOTIFICATION_CLOSED_OPCODE, reason as u64),
+    };
+    SmallMessage::new(0, opcode)
+        .with_data(0, event.id() as u64)
+        .with_data(1, value)
+}
+
+/// Push `event` to the endpoint that posted the notification. A full queue
+/// or a vanished endpoint only loses this event.
+pub fn send_event(endpoint: EndpointId, event: NotificationEvent) {
+    let msg = Message::Small(encode_event(event));
+    let _ = crate::ipc::message_passing::send_to_endpoint(msg, endpoint);
 }
 
 // ---------------------------------------------------------------------------
@@ -164,19 +342,21 @@ impl NotificationIpcServer {
     /// - For `Notify`: the assigned notification ID.
     /// - For `Dismiss`/`DismissAll`: 0 on success.
     /// - For `GetActive`: the count of active notifications.
+    /// - For `Status`: a `STATUS_*` code or close reason.
     pub fn handle_message(&self, msg: &NotificationMessage) -> Result<u32, KernelError> {
         match msg.msg_type {
             NotificationMessageType::Notify => {
-                let urgency = NotificationUrgency::from_u8(msg.urgency);
-                let id = notification::with_notification_manager(|mgr| {
-                    mgr.notify(
-                        msg.summary.clone(),
-                        msg.body.clone(),
-                        urgency,
-                        msg.app_name.clone(),
-                    )
-                })
-                .ok_or(KernelError::InvalidState {
+                let request = NotificationRequest {
+                    summary: msg.summary.clone(),
+                    body: msg.body.clone(),
+                    urgency: NotificationUrgency::from_u8(msg.urgency),
+                    app_name: msg.app_name.clone(),
+                    icon: msg.icon.clone(),
+                    timeout_ms: msg.timeout_ms,
+                    actions: msg.actions.clone(),
+                    reply_endpoint: msg.reply_endpoint,
+                };
+                let id = notification::post(request).ok_or(KernelError::InvalidState {
                     expected: "notification_manager_initialized",
                     actual: "not_initialized",
                 })?;
@@ -199,6 +379,17 @@ impl NotificationIpcServer {
                     notification::with_notification_manager(|mgr| mgr.active_count()).unwrap_or(0);
                 Ok(count as u32)
             }
+
+            NotificationMessageType::Status => {
+                let status =
+                    notification::with_notification_manager(|mgr| mgr.status(msg.notification_id))
+                        .flatten()
+                        .ok_or(KernelError::NotFound {
+                            resource: "notification",
+                            id: msg.notification_id as u64,
+                        })?;
+                Ok(encode_status(status))
+            }
         }
     }
 }
@@ -224,3 +415,93 @@ pub fn init() -> Result<(), KernelError> {
 pub fn is_initialized() -> bool {
     INITIALIZED.load(Ordering::Acquire)
 }
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::desktop::notification::CloseReason;
+
+    #[test]
+    fn test_message_type_from_u8() {
+        for v in 0..=4 {
+            assert_eq!(
+                NotificationMessageType::from_u8(v).map(|t| t as u8),
+                Some(v)
+            );
+        }
+        assert_eq!(NotificationMessageType::from_u8(5), None);
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let mut msg = NotificationMessage::new_notify("Build failed", "3 errors", 2, "make");
+        msg.icon = Some(String::from("/usr/share/icons/error.png"));
+        msg.timeout_ms = Some(0);
+        msg.reply_endpoint = Some(0x42);
+        msg.actions = vec![
+            NotificationAction {
+                key: String::from("default"),
+                label: String::new(),
+            },
+            NotificationAction {
+                key: String::from("log"),
+                label: String::from("Show log"),
+            },
+        ];
+        assert_eq!(NotificationMessage::decode(&msg.encode()), Some(msg));
+
+        let plain = NotificationMessage::new_status(7);
+        let buf = plain.encode();
+        assert_eq!(buf.len(), HEADER_SIZE + 4 * 2 + 1);
+        assert_eq!(NotificationMessage::decode(&buf), Some(plain));
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        let buf = NotificationMessage::new_notify("a", "b", 1, "c").encode();
+        assert_eq!(NotificationMessage::decode(&buf[..HEADER_SIZE - 1]), None);
+        // Truncated string, trailing bytes, unknown type
+        assert_eq!(NotificationMessage::decode(&buf[..buf.len() - 2]), None);
+        let mut long = buf.clone();
+        long.push(0);
+        assert_eq!(NotificationMessage::decode(&long), None);
+        let mut bad = buf.clone();
+        bad[0] = 9;
+        assert_eq!(NotificationMessage::decode(&bad), None);
+        // Too many actions
+        let mut many = buf;
+        let last = many.len() - 1;
+        many[last] = notification::MAX_ACTIONS as u8 + 1;
+        assert_eq!(NotificationMessage::decode(&many), None);
+    }
+
+    #[test]
+    fn test_event_and_status_encoding() {
+        let msg = encode_event(NotificationEvent::ActionInvoked { id: 5, action: 2 });
+        assert_eq!(msg.opcode, NOTIFICATION_ACTION_OPCODE);
+        assert_eq!((msg.data[0], msg.data[1]), (5, 2));
+
+        let msg = encode_event(NotificationEvent::Closed {
+            id: 5,
+            reason: CloseReason::Expired,
+        });
+        assert_eq!(msg.opcode, NOTIFICATION_CLOSED_OPCODE);
+        assert_eq!((msg.data[0], msg.data[1]), (5, 1));
+
+        assert_eq!(encode_status(NotificationStatus::Active), STATUS_ACTIVE);
+        assert_eq!(
+            encode_status(NotificationStatus::ActionInvoked(1)),
+            STATUS_ACTION_BASE + 1
+        );
+        assert_eq!(
+            encode_status(NotificationStatus::Closed(CloseReason::Dismissed)),
+            2
+        );
+    }
+}
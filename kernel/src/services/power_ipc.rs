@@ -0,0 +1,325 @@
+//! Power Status IPC Service
+//!
+//! Provides an IPC endpoint the desktop panel and power tools query for the
+//! battery level, AC state and thermal zone readings, instead of parsing
+//! `/sys/class/power_supply` and `/sys/class/thermal`.
+//!
+//! Replies are little-endian byte strings:
+//! - `GetPowerStatus`: 16 bytes -- AC state (0 = offline, 1 = online, 2 =
+//!   unknown), battery present (0/1), [`BatteryState`], capacity in percent,
+//!   then energy now (mWh), energy full (mWh) and power now (mW) as u32
+//! - `GetThermalZones`: a zone count, then per zone the temperature, passive
+//!   trip and critical trip in millidegrees Celsius as i32 (`i32::MIN`: none)
+
+#![allow(dead_code)]
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    error::KernelError,
+    power::{
+        battery::{self, BatteryState, PowerStatus},
+        thermal::{self, TripPoints, ZoneStatus},
+    },
+    services::desktop_ipc::DESKTOP_POWER_ENDPOINT,
+};
+
+// ---------------------------------------------------------------------------
+// Message types
+// ---------------------------------------------------------------------------
+
+/// Type of power IPC message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PowerMessageType {
+    /// Query the combined battery and AC adapter status.
+    GetPowerStatus = 0,
+    /// Query the readings of all thermal zones.
+    GetThermalZones = 1,
+}
+
+impl PowerMessageType {
+    /// Convert a raw u8 to a message type, if valid.
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Self::GetPowerStatus),
+            1 => Some(Self::GetThermalZones),
+            _ => None,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Wire format
+// ---------------------------------------------------------------------------
+
+/// Length of a `GetPowerStatus` reply.
+pub const POWER_STATUS_LEN: usize = 16;
+
+/// Length of one zone in a `GetThermalZones` reply.
+pub const THERMAL_ZONE_LEN: usize = 12;
+
+/// Most zones a `GetThermalZones` reply carries.
+pub const MAX_THERMAL_ZONES: usize = 8;
+
+/// Temperature value meaning "no reading" or "no trip".
+const NO_TEMP: i32 = i32::MIN;
+
+/// One zone of a `GetThermalZones` reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThermalZoneReply {
+    /// Current temperature, `None` without a valid reading.
+    pub temperature_mc: Option<i32>,
+    /// The zone's trip points.
+    pub trips: TripPoints,
+}
+
+/// Encode a power status reply.
+pub fn encode_power_status(status: &PowerStatus) -> [u8; POWER_STATUS_LEN] {
+    let mut buf = [0u8; POWER_STATUS_LEN];
+    buf[0] = match status.ac_online {
+        Some(false) => 0,
+        Some(true) => 1,
+        None => 2,
+    };
+    buf[1] = status.battery_present as u8;
+    buf[2] = status.state as u8;
+    buf[3] = status.capacity_percent;
+    buf[4..8].copy_from_slice(&status.energy_now_mwh.to_le_bytes());
+    buf[8..12].copy_from_slice(&status.energy_full_mwh.to_le_bytes());
+    buf[12..16].copy_from_slice(&status.power_now_mw.to_le_bytes());
+    buf
+}
+
+/// Decode a power status reply.
+pub fn decode_power_status(buf: &[u8]) -> Option<PowerStatus> {
+    if buf.len() < POWER_STATUS_LEN {
+        return None;
+    }
+    let u32_at = |at: usize| u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+    Some(PowerStatus {
+        ac_online: match buf[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        },
+        battery_present: buf[1] != 0,
+        state: BatteryState::from_u8(buf[2]),
+        capacity_percent: buf[3],
+        energy_now_mwh: u32_at(4),
+        energy_full_mwh: u32_at(8),
+        power_now_mw: u32_at(12),
+    })
+}
+
+/// Encode a thermal zones reply. Zones past `MAX_THERMAL_ZONES` are dropped.
+pub fn encode_thermal_zones(zones: &[ZoneStatus]) -> Vec<u8> {
+    let zones = &zones[..zones.len().min(MAX_THERMAL_ZONES)];
+    let mut buf = Vec::with_capacity(1 + zones.len() * THERMAL_ZONE_LEN);
+    buf.push(zones.len() as u8);
+    for zone in zones {
+        for temp in [
+            zone.temperature_mc,
+            zone.trips.passive_mc,
+            zone.trips.critical_mc,
+        ] {
+            buf.extend_from_slice(&temp.unwrap_or(NO_TEMP).to_le_bytes());
+        }
+    }
+    buf
+}
+
+/// Decode a thermal zones reply.
+pub fn decode_thermal_zones(buf: &[u8]) -> Option<Vec<ThermalZoneReply>> {
+    let (&count, rest) = buf.split_first()?;
+    let count = count as usize;
+    if count > MAX_THERMAL_ZONES || rest.len() < count * THERMAL_ZONE_LEN {
+        return None;
+    }
+    let temp_at = |at: usize| {
+        let value = i32::from_le_bytes([rest[at], rest[at + 1], rest[at + 2], rest[at + 3]]);
+        (value != NO_TEMP).then_some(value)
+    };
+    Some(
+        (0..count)
+            .map(|i| {
+                let base = i * THERMAL_ZONE_LEN;
+                ThermalZoneReply {
+                    temperature_mc: temp_at(base),
+                    trips: TripPoints {
+                        passive_mc: temp_at(base + 4),
+                        critical_mc: temp_at(base + 8),
+                    },
+                }
+            })
+            .collect(),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// IPC Server
+// ---------------------------------------------------------------------------
+
+/// Whether the power IPC server has been initialized.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Power IPC server that answers battery and thermal queries.
+pub struct PowerIpcServer {
+    /// The well-known endpoint ID this server is bound to.
+    endpoint_id: u64,
+}
+
+impl PowerIpcServer {
+    /// Create a new power IPC server instance.
+    pub fn new() -> Self {
+        Self {
+            endpoint_id: DESKTOP_POWER_ENDPOINT,
+        }
+    }
+
+    /// Initialize the power IPC server.
+    ///
+    /// The endpoint itself is registered by `desktop_ipc::init()`; this
+    /// marks the power service as ready to accept queries.
+    pub fn init(&self) -> Result<(), KernelError> {
+        if INITIALIZED.load(Ordering::Acquire) {
+            return Err(KernelError::InvalidState {
+                expected: "uninitialized",
+                actual: "initialized",
+            });
+        }
+
+        crate::println!(
+            "[POWER-IPC] Power IPC server bound to endpoint {}",
+            self.endpoint_id
+        );
+
+        INITIALIZED.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Handle an incoming query and return the encoded reply.
+    pub fn handle_message(&self, msg_type: PowerMessageType) -> Result<Vec<u8>, KernelError> {
+        match msg_type {
+            PowerMessageType::GetPowerStatus => {
+                Ok(encode_power_status(&battery::status()).to_vec())
+            }
+            PowerMessageType::GetThermalZones => Ok(encode_thermal_zones(&thermal::zones())),
+        }
+    }
+}
+
+impl Default for PowerIpcServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Module-level initialization
+// ---------------------------------------------------------------------------
+
+/// Initialize the power IPC service.
+pub fn init() -> Result<(), KernelError> {
+    let server = PowerIpcServer::new();
+    server.init()?;
+    Ok(())
+}
+
+/// Check whether the power IPC service has been initialized.
+pub fn is_initialized() -> bool {
+    INITIALIZED.load(Ordering::Acquire)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_type_from_u8() {
+        assert_eq!(
+            PowerMessageType::from_u8(0),
+            Some(PowerMessageType::GetPowerStatus)
+        );
+        assert_eq!(
+            PowerMessageType::from_u8(1),
+            Some(PowerMessageType::GetThermalZones)
+        );
+        assert_eq!(PowerMessageType::from_u8(2), None);
+    }
+
+    #[test]
+    fn test_power_status_round_trip() {
+        let status = PowerStatus {
+            ac_online: Some(false),
+            battery_present: true,
+            state: BatteryState::Discharging,
+            capacity_percent: 42,
+            energy_now_mwh: 21_000,
+            energy_full_mwh: 50_000,
+            power_now_mw: 7_500,
+        };
+        let buf = encode_power_status(&status);
+        assert_eq!(buf[0], 0);
+        assert_eq!(buf[3], 42);
+        assert_eq!(decode_power_status(&buf), Some(status));
+
+        let unknown = PowerStatus::default();
+        let buf = encode_power_status(&unknown);
+        assert_eq!(buf[0], 2);
+        assert_eq!(decode_power_status(&buf), Some(unknown));
+    }
+
+    #[test]
+    fn test_power_status_short_buffer() {
+        assert_eq!(decode_power_status(&[0u8; POWER_STATUS_LEN - 1]), None);
+    }
+
+    #[test]
+    fn test_thermal_zones_round_trip() {
+        let zones = [
+            ZoneStatus {
+                zone_type: "x86_pkg_temp",
+                temperature_mc: Some(55_000),
+                trips: TripPoints {
+                    passive_mc: Some(90_000),
+                    critical_mc: Some(100_000),
+                },
+            },
+            ZoneStatus {
+                zone_type: "acpitz",
+                temperature_mc: None,
+                trips: TripPoints::default(),
+            },
+        ];
+        let buf = encode_thermal_zones(&zones);
+        assert_eq!(buf.len(), 1 + 2 * THERMAL_ZONE_LEN);
+
+        let decoded = decode_thermal_zones(&buf).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].temperature_mc, Some(55_000));
+        assert_eq!(decoded[0].trips, zones[0].trips);
+        assert_eq!(decoded[1].temperature_mc, None);
+        assert_eq!(decoded[1].trips, TripPoints::default());
+    }
+
+    #[test]
+    fn test_thermal_zones_limits() {
+        let zone = ZoneStatus {
+            zone_type: "acpitz",
+            temperature_mc: Some(40_000),
+            trips: TripPoints::default(),
+        };
+        let buf = encode_thermal_zones(&[zone; MAX_THERMAL_ZONES + 2]);
+        assert_eq!(buf[0] as usize, MAX_THERMAL_ZONES);
+
+        assert_eq!(decode_thermal_zones(&[]), None);
+        assert_eq!(decode_thermal_zones(&[1, 0, 0]), None);
+        assert_eq!(decode_thermal_zones(&[0]), Some(Vec::new()));
+    }
+}
@@ -13,15 +13,19 @@ use crate::{
 };
 
 /// Resource limits for a process
+///
+/// Applied to the process's `RLIMIT_*` limits (see
+/// [`crate::process::rlimit`]) when it is spawned or the limits are changed.
 #[derive(Debug, Clone)]
 pub struct ResourceLimits {
-    pub max_memory: u64,   // Maximum memory in bytes
-    pub max_cpu_time: u64, // Maximum CPU time in microseconds
-    pub max_files: u32,    // Maximum open files
-    pub max_threads: u32,  // Maximum threads
-    pub nice_value: i8,    // Process nice value (-20 to 19)
-    pub stack_size: u64,   // Stack size limit
-    pub core_size: u64,    // Core dump size limit
+    pub max_memory: u64,    // Maximum address space in bytes (RLIMIT_AS)
+    pub max_cpu_time: u64,  // Maximum CPU time in microseconds (RLIMIT_CPU)
+    pub max_files: u32,     // Maximum open files (RLIMIT_NOFILE)
+    pub max_processes: u32, // Maximum processes of the user (RLIMIT_NPROC)
+    pub max_threads: u32,   // Maximum threads
+    pub nice_value: i8,     // Process nice value (-20 to 19)
+    pub stack_size: u64,    // Stack size limit (RLIMIT_STACK)
+    pub core_size: u64,     // Core dump size limit (RLIMIT_CORE)
 }
 
 impl Default for ResourceLimits {
@@ -30,6 +34,7 @@ impl Default for ResourceLimits {
             max_memory: 256 * 1024 * 1024, // 256 MB
             max_cpu_time: u64::MAX,        // Unlimited
             max_files: 1024,               // 1024 files
+            max_processes: 1024,           // 1024 processes
             max_threads: 256,              // 256 threads
             nice_value: 0,                 // Normal priority
             stack_size: 8 * 1024 * 1024,   // 8 MB
@@ -38,6 +43,55 @@ impl Default for ResourceLimits {
     }
 }
 
+impl ResourceLimits {
+    /// No limits beyond the system-wide ones; a service's unit file narrows
+    /// them down.
+    pub fn unlimited() -> Self {
+        Self {
+            max_memory: u64::MAX,
+            max_cpu_time: u64::MAX,
+            max_files: u32::MAX,
+            max_processes: u32::MAX,
+            max_threads: u32::MAX,
+            nice_value: 0,
+            stack_size: 8 * 1024 * 1024,
+            core_size: u64::MAX,
+        }
+    }
+
+    /// Set `process`'s soft and hard `RLIMIT_*` limits to these values.
+    pub fn apply(&self, process: &crate::process::Process) -> Result<(), KernelError> {
+        use crate::process::rlimit::{
+            RLIMIT_AS, RLIMIT_CORE, RLIMIT_CPU, RLIMIT_NOFILE, RLIMIT_NPROC, RLIMIT_STACK,
+            RLIM_INFINITY,
+        };
+
+        let count = |n: u32| {
+            if n == u32::MAX {
+                RLIM_INFINITY
+            } else {
+                n as u64
+            }
+        };
+        let cpu_secs = if self.max_cpu_time == u64::MAX {
+            RLIM_INFINITY
+        } else {
+            self.max_cpu_time.div_ceil(1_000_000)
+        };
+        for (resource, limit) in [
+            (RLIMIT_AS, self.max_memory),
+            (RLIMIT_CPU, cpu_secs),
+            (RLIMIT_NOFILE, count(self.max_files)),
+            (RLIMIT_NPROC, count(self.max_processes)),
+            (RLIMIT_STACK, self.stack_size),
+            (RLIMIT_CORE, self.core_size),
+        ] {
+            process.rlimits.set(resource, limit, limit, true)?;
+        }
+        Ok(())
+    }
+}
+
 /// Process information
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
@@ -60,6 +114,18 @@ pub struct ProcessInfo {
     pub terminal: Option<String>,
 }
 
+impl ProcessInfo {
+    /// Share of one CPU the process has used since it started, in tenths of
+    /// a percent (the lifetime average `ps` reports).
+    pub fn cpu_permille(&self) -> u64 {
+        let lifetime = system_time_us().saturating_sub(self.start_time);
+        if lifetime == 0 {
+            return 0;
+        }
+        self.cpu_time.saturating_mul(1000) / lifetime
+    }
+}
+
 /// Process state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessState {
@@ -167,7 +233,7 @@ impl Default for ProcessServer {
 }
 
 impl ProcessServer {
-    /// Create a new process
+    /// Create a new process with resource `limits`
     pub fn create_process(
         &self,
         parent_pid: ProcessId,
@@ -176,6 +242,7 @@ impl ProcessServer {
         gid: u32,
         command_line: Vec<String>,
         environment: Vec<String>,
+        limits: ResourceLimits,
     ) -> Result<ProcessId, KernelError> {
         let pid = ProcessId(self.next_pid.fetch_add(1, Ordering::SeqCst));
 
@@ -209,8 +276,10 @@ impl ProcessServer {
             terminal: None,
         };
 
-        // Set default resource limits
-        let limits = ResourceLimits::default();
+        // Enforce the limits on the live process, if there is one
+        if let Some(process) = crate::process::get_process(pid) {
+            limits.apply(process)?;
+        }
 
         // Add to process table
         self.processes.write().insert(pid.0, info);
@@ -313,21 +382,33 @@ impl ProcessServer {
 
     /// Get process information
     pub fn get_process_info(&self, pid: ProcessId) -> Option<ProcessInfo> {
-        self.processes.read().get(&pid.0).cloned()
+        self.processes
+            .read()
+            .get(&pid.0)
+            .cloned()
+            .map(with_live_stats)
     }
 
     /// List all processes
     pub fn list_processes(&self) -> Vec<ProcessInfo> {
-        self.processes.read().values().cloned().collect()
+        self.processes
+            .read()
+            .values()
+            .cloned()
+            .map(with_live_stats)
+            .collect()
     }
 
-    /// Set resource limits for a process
+    /// Set resource limits for a process, enforcing them on the live process
     pub fn set_resource_limits(
         &self,
         pid: ProcessId,
         limits: ResourceLimits,
     ) -> Result<(), KernelError> {
         if self.processes.read().contains_key(&pid.0) {
+            if let Some(process) = crate::process::get_process(pid) {
+                limits.apply(process)?;
+            }
             self.resource_limits.write().insert(pid.0, limits);
             Ok(())
         } else {
@@ -511,9 +592,7 @@ impl ProcessServer {
     // Helper functions
 
     fn get_system_time(&self) -> u64 {
-        // Read the hardware timestamp counter and convert to approximate
-        // microseconds (assumes 2 GHz clock: 1 cycle = 0.5 ns, 2000 cycles = 1 us).
-        crate::arch::entropy::read_timestamp() / 2000
+        system_time_us()
     }
 
     fn remove_from_session_and_group(&self, pid: ProcessId) {
@@ -577,3 +656,19 @@ pub fn get_process_server() -> &'static ProcessServer {
         .get()
         .expect("Process server not initialized: init() was not called")
 }
+
+/// Microseconds since boot, the clock of `start_time`.
+fn system_time_us() -> u64 {
+    crate::arch::timer::get_timestamp_ms() * 1000
+}
+
+/// `info` with the CPU time, resident size and thread count the kernel has
+/// accounted to the live process, if it still exists.
+fn with_live_stats(mut info: ProcessInfo) -> ProcessInfo {
+    if let Some(process) = crate::process::get_process(info.pid) {
+        info.cpu_time = process.get_cpu_time();
+        info.memory_usage = process.memory_stats.resident_size.load(Ordering::Relaxed);
+        info.thread_count = process.thread_count() as u32;
+    }
+    info
+}
@@ -0,0 +1,306 @@
+//! Desktop Settings IPC Service
+//!
+//! Provides an IPC endpoint through which user-space reads and changes the
+//! desktop configuration (`desktop::config`) and subscribes to changes,
+//! so clients pick up a new theme or wallpaper without re-reading
+//! `/etc/veridian/desktop.toml`.
+//!
+//! Requests and replies are byte strings:
+//! - `Get`: the setting's `section.key` name; replies with its value
+//! - `Set`: name and value separated by a NUL byte; empty reply
+//! - `List`: empty; replies with the whole configuration as TOML
+//! - `Subscribe` / `Unsubscribe`: the subscriber's endpoint ID as a
+//!   little-endian u64; empty reply
+//!
+//! After each change every subscriber receives a small message with opcode
+//! [`SETTINGS_CHANGED_OPCODE`], the new generation in data register 0 and
+//! the setting's ID (`config::Setting::id`) in data register 1.
+
+#![allow(dead_code)]
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use spin::Mutex;
+
+use crate::{
+    desktop::config::{self, Setting},
+    error::KernelError,
+    ipc::{message::SmallMessage, EndpointId, Message},
+    services::desktop_ipc::DESKTOP_SETTINGS_ENDPOINT,
+};
+
+// ---------------------------------------------------------------------------
+// Message types
+// ---------------------------------------------------------------------------
+
+/// Type of settings IPC message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SettingsMessageType {
+    /// Read one setting.
+    Get = 0,
+    /// Change one setting.
+    Set = 1,
+    /// Read the whole configuration.
+    List = 2,
+    /// Receive change notifications on an endpoint.
+    Subscribe = 3,
+    /// Stop receiving change notifications.
+    Unsubscribe = 4,
+}
+
+impl SettingsMessageType {
+    /// Convert a raw u8 to a message type, if valid.
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Self::Get),
+            1 => Some(Self::Set),
+            2 => Some(Self::List),
+            3 => Some(Self::Subscribe),
+            4 => Some(Self::Unsubscribe),
+            _ => None,
+        }
+    }
+}
+
+/// Opcode of the change notification pushed to subscribers.
+pub const SETTINGS_CHANGED_OPCODE: u32 = 0x5E7C;
+
+/// Most endpoints that can subscribe at once.
+pub const MAX_SUBSCRIBERS: usize = 32;
+
+// ---------------------------------------------------------------------------
+// Wire format
+// ---------------------------------------------------------------------------
+
+/// Encode a `Set` request.
+pub fn encode_set(key: &str, value: &str) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(key.len() + 1 + value.len());
+    buf.extend_from_slice(key.as_bytes());
+    buf.push(0);
+    buf.extend_from_slice(value.as_bytes());
+    buf
+}
+
+/// Decode a `Set` request into name and value.
+pub fn decode_set(buf: &[u8]) -> Option<(&str, &str)> {
+    let split = buf.iter().position(|&b| b == 0)?;
+    let key = core::str::from_utf8(&buf[..split]).ok()?;
+    let value = core::str::from_utf8(&buf[split + 1..]).ok()?;
+    Some((key, value))
+}
+
+/// Encode a `Subscribe` or `Unsubscribe` request.
+pub fn encode_endpoint(endpoint: EndpointId) -> [u8; 8] {
+    endpoint.to_le_bytes()
+}
+
+/// Decode a `Subscribe` or `Unsubscribe` request.
+pub fn decode_endpoint(buf: &[u8]) -> Option<EndpointId> {
+    Some(EndpointId::from_le_bytes(buf.try_into().ok()?))
+}
+
+/// Build the change notification for `setting`.
+pub fn encode_changed(setting: Setting, generation: u64) -> SmallMessage {
+    SmallMessage::new(0, SETTINGS_CHANGED_OPCODE)
+        .with_data(0, generation)
+        .with_data(1, setting.id())
+}
+
+/// Decode a change notification into the setting and generation.
+pub fn decode_changed(msg: &SmallMessage) -> Option<(Setting, u64)> {
+    if msg.opcode != SETTINGS_CHANGED_OPCODE {
+        return None;
+    }
+    Some((Setting::from_id(msg.data[1])?, msg.data[0]))
+}
+
+// ---------------------------------------------------------------------------
+// Subscribers
+// ---------------------------------------------------------------------------
+
+/// Endpoints that receive change notifications.
+static SUBSCRIBERS: Mutex<Vec<EndpointId>> = Mutex::new(Vec::new());
+
+/// Add a subscriber. Subscribing twice is harmless.
+pub fn subscribe(endpoint: EndpointId) -> Result<(), KernelError> {
+    let mut subscribers = SUBSCRIBERS.lock();
+    if subscribers.contains(&endpoint) {
+        return Ok(());
+    }
+    if subscribers.len() >= MAX_SUBSCRIBERS {
+        return Err(KernelError::ResourceExhausted {
+            resource: "settings subscribers",
+        });
+    }
+    subscribers.push(endpoint);
+    Ok(())
+}
+
+/// Remove a subscriber.
+pub fn unsubscribe(endpoint: EndpointId) {
+    SUBSCRIBERS.lock().retain(|&e| e != endpoint);
+}
+
+/// Tell every subscriber that `setting` changed. Endpoints that no longer
+/// exist are dropped; a full queue only loses this one notification.
+pub fn notify_changed(setting: Setting, generation: u64) {
+    let msg = Message::Small(encode_changed(setting, generation));
+    SUBSCRIBERS.lock().retain(|&endpoint| {
+        !matches!(
+            crate::ipc::message_passing::send_to_endpoint(msg, endpoint),
+            Err(crate::ipc::IpcError::EndpointNotFound)
+        )
+    });
+}
+
+// ---------------------------------------------------------------------------
+// IPC Server
+// ---------------------------------------------------------------------------
+
+/// Whether the settings IPC server has been initialized.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Settings IPC server that answers configuration queries and changes.
+pub struct SettingsIpcServer {
+    /// The well-known endpoint ID this server is bound to.
+    endpoint_id: u64,
+}
+
+impl SettingsIpcServer {
+    /// Create a new settings IPC server instance.
+    pub fn new() -> Self {
+        Self {
+            endpoint_id: DESKTOP_SETTINGS_ENDPOINT,
+        }
+    }
+
+    /// Initialize the settings IPC server.
+    ///
+    /// The endpoint itself is registered by `desktop_ipc::init()`; this
+    /// marks the settings service as ready to accept requests.
+    pub fn init(&self) -> Result<(), KernelError> {
+        if INITIALIZED.load(Ordering::Acquire) {
+            return Err(KernelError::InvalidState {
+                expected: "uninitialized",
+                actual: "initialized",
+            });
+        }
+
+        crate::println!(
+            "[SETTINGS-IPC] Settings IPC server bound to endpoint {}",
+            self.endpoint_id
+        );
+
+        INITIALIZED.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Handle an incoming request and return the encoded reply.
+    pub fn handle_message(
+        &self,
+        msg_type: SettingsMessageType,
+        payload: &[u8],
+    ) -> Result<Vec<u8>, KernelError> {
+        let bad_request = |value| KernelError::InvalidArgument {
+            name: "settings request",
+            value,
+        };
+        match msg_type {
+            SettingsMessageType::Get => {
+                let setting = core::str::from_utf8(payload)
+                    .ok()
+                    .and_then(Setting::parse)
+                    .ok_or(bad_request("unknown setting"))?;
+                Ok(config::get(setting).into_bytes())
+            }
+            SettingsMessageType::Set => {
+                let (key, value) = decode_set(payload).ok_or(bad_request("malformed"))?;
+                let setting = Setting::parse(key).ok_or(bad_request("unknown setting"))?;
+                config::set(setting, value)?;
+                Ok(Vec::new())
+            }
+            SettingsMessageType::List => Ok(config::with_config(|c| c.to_toml()).into_bytes()),
+            SettingsMessageType::Subscribe => {
+                subscribe(decode_endpoint(payload).ok_or(bad_request("malformed"))?)?;
+                Ok(Vec::new())
+            }
+            SettingsMessageType::Unsubscribe => {
+                unsubscribe(decode_endpoint(payload).ok_or(bad_request("malformed"))?);
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+impl Default for SettingsIpcServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Module-level initialization
+// ---------------------------------------------------------------------------
+
+/// Initialize the settings IPC service.
+pub fn init() -> Result<(), KernelError> {
+    let server = SettingsIpcServer::new();
+    server.init()?;
+    Ok(())
+}
+
+/// Check whether the settings IPC service has been initialized.
+pub fn is_initialized() -> bool {
+    INITIALIZED.load(Ordering::Acquire)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop::desktop_ext::theme::ColorSlot;
+
+    #[test]
+    fn test_message_type_from_u8() {
+        for v in 0..=4 {
+            assert_eq!(SettingsMessageType::from_u8(v).map(|t| t as u8), Some(v));
+        }
+        assert_eq!(SettingsMessageType::from_u8(5), None);
+    }
+
+    #[test]
+    fn test_set_round_trip() {
+        let buf = encode_set("colors.accent", "#88C0D0");
+        assert_eq!(decode_set(&buf), Some(("colors.accent", "#88C0D0")));
+
+        // An empty value clears a setting.
+        let buf = encode_set("appearance.wallpaper", "");
+        assert_eq!(decode_set(&buf), Some(("appearance.wallpaper", "")));
+
+        assert_eq!(decode_set(b"appearance.theme"), None);
+        assert_eq!(decode_set(&[0xFF, 0, b'x']), None);
+    }
+
+    #[test]
+    fn test_endpoint_round_trip() {
+        let buf = encode_endpoint(0x1234_5678_9ABC);
+        assert_eq!(decode_endpoint(&buf), Some(0x1234_5678_9ABC));
+        assert_eq!(decode_endpoint(&buf[..7]), None);
+    }
+
+    #[test]
+    fn test_changed_round_trip() {
+        let setting = Setting::Color(ColorSlot::PanelBackground);
+        let msg = encode_changed(setting, 42);
+        assert_eq!(msg.opcode, SETTINGS_CHANGED_OPCODE);
+        assert_eq!(decode_changed(&msg), Some((setting, 42)));
+
+        let other = SmallMessage::new(0, 7).with_data(1, setting.id());
+        assert_eq!(decode_changed(&other), None);
+    }
+}
@@ -10,7 +10,9 @@ use crate::services::shell::{BuiltinCommand, CommandResult, Shell};
 // Desktop / GUI Commands
 // ============================================================================
 
+#[cfg(feature = "desktop")]
 pub(in crate::services::shell) struct StartGuiCommand;
+#[cfg(feature = "desktop")]
 impl BuiltinCommand for StartGuiCommand {
     fn name(&self) -> &str {
         "startgui"
@@ -66,7 +68,9 @@ impl BuiltinCommand for StartGuiCommand {
     }
 }
 
+#[cfg(feature = "desktop")]
 pub(in crate::services::shell) struct WinfoCommand;
+#[cfg(feature = "desktop")]
 impl BuiltinCommand for WinfoCommand {
     fn name(&self) -> &str {
         "winfo"
@@ -185,24 +189,29 @@ impl BuiltinCommand for PlayCommand {
             }
         };
 
-        // Create an audio stream, write samples, and play
-        let config = wav.to_audio_config();
-        let result =
-            crate::audio::client::with_client(|client: &mut crate::audio::client::AudioClient| {
-                let stream_id = client.create_stream(path, config)?;
-                client.write_samples(stream_id, &samples)?;
-                client.play(stream_id)?;
+        // The mixer runs at 48 kHz: resample other rates up front
+        let output_rate = crate::audio::AudioConfig::default_config().sample_rate;
+        let channels = wav.num_channels as usize;
+        let samples =
+            crate::audio::wav::resample_linear(&samples, channels, wav.sample_rate, output_rate);
+        let config = crate::audio::AudioConfig {
+            sample_rate: output_rate,
+            channels: wav.num_channels as u8,
+            format: crate::audio::SampleFormat::S16Le,
+            buffer_frames: 0,
+        };
+
+        // Create a stream and feed it as the output device drains it
+        match play_samples(path, config, &samples) {
+            Ok(Ok(_stream_id)) => {
                 crate::println!(
                     "Queued {} samples ({} ms) on stream {}",
                     samples.len(),
                     wav.duration_ms(),
-                    stream_id.as_u32()
+                    _stream_id
                 );
-                Ok::<(), crate::error::KernelError>(())
-            });
-
-        match result {
-            Ok(Ok(())) => CommandResult::Success(0),
+                CommandResult::Success(0)
+            }
             Ok(Err(e)) => {
                 crate::println!("play: audio error: {:?}", e);
                 CommandResult::Error(format!("audio error: {:?}", e))
@@ -215,6 +224,40 @@ impl BuiltinCommand for PlayCommand {
     }
 }
 
+/// Play `samples` on a new stream, waiting until all of them are queued.
+///
+/// Without an output device nothing drains the stream, so only what fits
+/// into its buffer is queued. Returns the stream ID.
+fn play_samples(
+    name: &str,
+    config: crate::audio::AudioConfig,
+    samples: &[i16],
+) -> Result<Result<u32, crate::error::KernelError>, crate::error::KernelError> {
+    use crate::audio::client::with_client;
+
+    let stream_id = match with_client(|client| client.create_stream(name, config))? {
+        Ok(id) => id,
+        Err(e) => return Ok(Err(e)),
+    };
+    if let Err(e) = with_client(|client| client.play(stream_id))? {
+        return Ok(Err(e));
+    }
+
+    let mut offset = 0;
+    while offset < samples.len() {
+        match with_client(|client| client.write_samples(stream_id, &samples[offset..]))? {
+            Ok(0) if !crate::audio::pipeline::has_output() => break,
+            Ok(0) => crate::sched::yield_cpu(),
+            Ok(written) => {
+                offset += written;
+                crate::audio::pipeline::pump();
+            }
+            Err(e) => return Ok(Err(e)),
+        }
+    }
+    Ok(Ok(stream_id.as_u32()))
+}
+
 pub(in crate::services::shell) struct VolumeCommand;
 impl BuiltinCommand for VolumeCommand {
     fn name(&self) -> &str {
@@ -310,7 +353,9 @@ impl BuiltinCommand for VolumeCommand {
     }
 }
 
+#[cfg(feature = "desktop")]
 pub(in crate::services::shell) struct BrowserCommand;
+#[cfg(feature = "desktop")]
 impl BuiltinCommand for BrowserCommand {
     fn name(&self) -> &str {
         "browser"
@@ -357,7 +402,9 @@ impl BuiltinCommand for BrowserCommand {
 // Desktop Enhancement Commands
 // ============================================================================
 
+#[cfg(feature = "desktop")]
 pub(in crate::services::shell) struct ScreenshotCommand;
+#[cfg(feature = "desktop")]
 impl BuiltinCommand for ScreenshotCommand {
     fn name(&self) -> &str {
         "screenshot"
@@ -365,52 +412,25 @@ impl BuiltinCommand for ScreenshotCommand {
     fn description(&self) -> &str {
         "Capture a screenshot"
     }
-    fn execute(&self, _args: &[String], _shell: &Shell) -> CommandResult {
-        // Check framebuffer dimensions via Wayland compositor
-        let display_size = crate::desktop::wayland::with_display(|d| d.wl_compositor.output_size());
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        let path = args
+            .first()
+            .map(String::as_str)
+            .unwrap_or("/tmp/screenshot.png");
 
-        let (width, height) = match display_size {
-            Some((w, h)) if w > 0 && h > 0 => (w, h),
-            _ => {
+        let shot = match crate::desktop::screenshot::capture() {
+            Ok(shot) => shot,
+            Err(_) => {
                 crate::println!("screenshot: no framebuffer available (desktop not running?)");
                 return CommandResult::Error(String::from("no framebuffer"));
             }
         };
 
-        crate::println!("Capturing screenshot ({}x{})...", width, height);
-
-        // Build a minimal BMP file (BITMAPINFOHEADER, 32bpp, top-down)
-        let row_bytes = width * 4; // 32bpp, no padding needed
-        let pixel_data_size = row_bytes * height;
-        let file_header_size: u32 = 14;
-        let dib_header_size: u32 = 40;
-        let pixel_offset = file_header_size + dib_header_size;
-        let file_size = pixel_offset + pixel_data_size;
-
-        let mut bmp = alloc::vec::Vec::with_capacity(file_size as usize);
-
-        // -- 14-byte BMP file header --
-        bmp.push(b'B');
-        bmp.push(b'M');
-        bmp.extend_from_slice(&file_size.to_le_bytes()); // file size
-        bmp.extend_from_slice(&[0u8; 4]); // reserved
-        bmp.extend_from_slice(&pixel_offset.to_le_bytes()); // pixel data offset
-
-        // -- 40-byte DIB header (BITMAPINFOHEADER) --
-        bmp.extend_from_slice(&dib_header_size.to_le_bytes()); // header size
-        bmp.extend_from_slice(&(width as i32).to_le_bytes()); // width
-        bmp.extend_from_slice(&(-(height as i32)).to_le_bytes()); // height (negative = top-down)
-        bmp.extend_from_slice(&1u16.to_le_bytes()); // planes
-        bmp.extend_from_slice(&32u16.to_le_bytes()); // bpp
-        bmp.extend_from_slice(&[0u8; 24]); // compression + rest zeros
-
-        // Fill pixel area with black (placeholder scanlines)
-        bmp.resize(file_size as usize, 0u8);
-
-        // Write via VFS
-        match crate::fs::write_file("/tmp/screenshot.bmp", &bmp) {
+        crate::println!("Capturing screenshot ({}x{})...", shot.width, shot.height);
+
+        match shot.save(path) {
             Ok(bytes) => {
-                crate::println!("Screenshot saved to /tmp/screenshot.bmp ({} bytes)", bytes);
+                crate::println!("Screenshot saved to {} ({} bytes)", path, bytes);
                 CommandResult::Success(0)
             }
             Err(e) => {
@@ -421,7 +441,87 @@ impl BuiltinCommand for ScreenshotCommand {
     }
 }
 
+pub(in crate::services::shell) struct InputRecCommand;
+impl BuiltinCommand for InputRecCommand {
+    fn name(&self) -> &str {
+        "inputrec"
+    }
+    fn description(&self) -> &str {
+        "Record and replay input events for GUI tests"
+    }
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::drivers::input_replay;
+
+        let usage = || {
+            crate::println!("Usage: inputrec record <file>");
+            crate::println!("       inputrec mark");
+            crate::println!("       inputrec stop");
+            crate::println!("       inputrec replay <file> [refdir]");
+            crate::println!("       inputrec queue <file> [refdir]");
+            crate::println!("       inputrec status");
+            CommandResult::Success(1)
+        };
+
+        let result = match args.first().map(String::as_str) {
+            Some("record") => match args.get(1) {
+                Some(path) => input_replay::start_recording(path)
+                    .map(|()| crate::println!("Recording input to {}", path)),
+                None => return usage(),
+            },
+            Some("mark") => {
+                input_replay::mark().map(|i| crate::println!("Checkpoint {} marked", i))
+            }
+            Some("stop") => input_replay::stop().map(|_| ()),
+            Some("replay") => match args.get(1) {
+                Some(path) => {
+                    input_replay::start_replay(path, args.get(2).map(String::as_str)).map(|_| ())
+                }
+                None => return usage(),
+            },
+            Some("queue") => match args.get(1) {
+                Some(path) => {
+                    let refs = args.get(2).map(String::as_str);
+                    input_replay::queue_replay(path, refs).map(|_| ())
+                }
+                None => return usage(),
+            },
+            Some("status") | None => {
+                let st = input_replay::status();
+                if st.recording {
+                    crate::println!(
+                        "Recording: {} events, {} checkpoints",
+                        st.events,
+                        st.checkpoints
+                    );
+                } else if st.replaying {
+                    crate::println!(
+                        "Replaying: {}/{} events, {} checkpoints ({} mismatches)",
+                        st.events,
+                        st.total,
+                        st.checkpoints,
+                        st.mismatches
+                    );
+                } else {
+                    crate::println!("No input session active");
+                }
+                Ok(())
+            }
+            Some(_) => return usage(),
+        };
+
+        match result {
+            Ok(()) => CommandResult::Success(0),
+            Err(e) => {
+                crate::println!("inputrec: {:?}", e);
+                CommandResult::Error(format!("inputrec: {:?}", e))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "desktop")]
 pub(in crate::services::shell) struct NotifyCommand;
+#[cfg(feature = "desktop")]
 impl BuiltinCommand for NotifyCommand {
     fn name(&self) -> &str {
         "notify"
@@ -446,7 +546,9 @@ impl BuiltinCommand for NotifyCommand {
     }
 }
 
+#[cfg(feature = "desktop")]
 pub(in crate::services::shell) struct ThemeCommand;
+#[cfg(feature = "desktop")]
 impl BuiltinCommand for ThemeCommand {
     fn name(&self) -> &str {
         "theme"
@@ -455,69 +557,36 @@ impl BuiltinCommand for ThemeCommand {
         "Manage desktop themes"
     }
     fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
-        use crate::desktop::desktop_ext::theme::{ThemeManager, ThemePreset};
+        use crate::desktop::{
+            config::{self, Setting},
+            desktop_ext::theme::ThemePreset,
+        };
 
-        if args.is_empty() {
-            let manager = ThemeManager::new();
-            let current = manager.current_preset();
-            crate::println!("Current theme: {:?}", current);
-            crate::println!("Usage: theme list|set <name>");
-            return CommandResult::Success(0);
-        }
-        match args[0].as_str() {
-            "list" => {
-                let manager = ThemeManager::new();
-                let current = manager.current_preset();
-                let presets = [
-                    ("Light", ThemePreset::Light),
-                    ("Dark", ThemePreset::Dark),
-                    ("SolarizedDark", ThemePreset::SolarizedDark),
-                    ("SolarizedLight", ThemePreset::SolarizedLight),
-                    ("Nord", ThemePreset::Nord),
-                    ("Dracula", ThemePreset::Dracula),
-                ];
+        let current = config::with_config(|c| c.theme);
+        match args.first().map(String::as_str) {
+            Some("list") => {
                 crate::println!("Available themes:");
-                for (i, (name, preset)) in presets.iter().enumerate() {
+                for (i, preset) in ThemePreset::ALL.iter().enumerate() {
                     let marker = if *preset == current { " (active)" } else { "" };
-                    crate::println!("  {}. {}{}", i + 1, name, marker);
+                    crate::println!("  {}. {}{}", i + 1, preset.name(), marker);
                 }
             }
-            "set" => {
-                if args.len() < 2 {
+            Some("set") => {
+                let Some(name) = args.get(1) else {
                     crate::println!("Usage: theme set <name>");
-                    crate::println!(
-                        "Names: Light, Dark, SolarizedDark, SolarizedLight, Nord, Dracula"
-                    );
-                } else {
-                    let preset = match args[1].to_lowercase().as_str() {
-                        "light" => Some(ThemePreset::Light),
-                        "dark" => Some(ThemePreset::Dark),
-                        "solarizeddark" | "solarized-dark" => Some(ThemePreset::SolarizedDark),
-                        "solarizedlight" | "solarized-light" => Some(ThemePreset::SolarizedLight),
-                        "nord" => Some(ThemePreset::Nord),
-                        "dracula" => Some(ThemePreset::Dracula),
-                        _ => None,
-                    };
-                    match preset {
-                        Some(p) => {
-                            let mut manager = ThemeManager::new();
-                            manager.set_theme(p);
-                            crate::println!("Theme set to: {:?}", p);
-                        }
-                        None => {
-                            crate::println!("theme: unknown theme '{}'", args[1]);
-                            crate::println!(
-                                "Names: Light, Dark, SolarizedDark, SolarizedLight, Nord, Dracula"
-                            );
-                            return CommandResult::Error(format!("unknown theme '{}'", args[1]));
-                        }
-                    }
+                    return CommandResult::Success(0);
+                };
+                if ThemePreset::from_name(name).is_none() {
+                    crate::println!("theme: unknown theme '{}'", name);
+                    return CommandResult::Error(format!("unknown theme '{}'", name));
+                }
+                if let Err(e) = config::set(Setting::Theme, name) {
+                    return CommandResult::Error(format!("theme: {:?}", e));
                 }
+                crate::println!("Theme set to: {}", config::get(Setting::Theme));
             }
             _ => {
-                let manager = ThemeManager::new();
-                let current = manager.current_preset();
-                crate::println!("Current theme: {:?}", current);
+                crate::println!("Current theme: {}", current.name());
                 crate::println!("Usage: theme list|set <name>");
             }
         }
@@ -1,4 +1,4 @@
-//! Development tools commands (git, make, gdb, profiler, CI).
+//! Development tools commands (git, make, gdb, profiler, CI, wasm).
 
 #![allow(unused_variables, unused_assignments)]
 
@@ -305,7 +305,10 @@ impl BuiltinCommand for ProfilerCommand {
                     );
                 } else {
                     crate::perf::trace::enable();
-                    crate::println!("Profiler started (tracing enabled, 10 event types)");
+                    crate::println!(
+                        "Profiler started (tracing enabled, {} event types)",
+                        crate::perf::trace::NUM_EVENT_TYPES
+                    );
                 }
             }
             "stop" => {
@@ -436,3 +439,270 @@ impl BuiltinCommand for CiCommand {
         CommandResult::Success(0)
     }
 }
+
+// ============================================================================
+// WebAssembly Command
+// ============================================================================
+
+pub(in crate::services::shell) struct WasmCommand;
+impl BuiltinCommand for WasmCommand {
+    fn name(&self) -> &str {
+        "wasm"
+    }
+    fn description(&self) -> &str {
+        "Run sandboxed WebAssembly modules and extensions"
+    }
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::wasm;
+
+        if args.is_empty() {
+            crate::println!("Usage: wasm run|info|load|call|unload|list");
+            crate::println!(
+                "  run [--fuel N|none] [--mem PAGES] [--dir GUEST[:HOST][:ro]] [--env K=V] \
+                 [--allow stdin,clock,random] <file> [args...]"
+            );
+            crate::println!("  info <file>");
+            crate::println!("  load [--dir ...] [--allow ...] <name> <file>");
+            crate::println!("  call <name> <export> [args...]");
+            crate::println!("  unload <name>");
+            return CommandResult::Success(1);
+        }
+        match args[0].as_str() {
+            "run" => {
+                let opts = match WasmOptions::parse(&args[1..]) {
+                    Ok(opts) => opts,
+                    Err(e) => return CommandResult::Error(format!("wasm: {}", e)),
+                };
+                let Some(path) = opts.rest.first() else {
+                    return CommandResult::Error(String::from("wasm run: missing file"));
+                };
+                let bytes = match crate::fs::read_file(path) {
+                    Ok(bytes) => bytes,
+                    Err(e) => return CommandResult::Error(format!("wasm: {}: {}", path, e)),
+                };
+                let ctx = opts.context(opts.rest.clone(), wasm::WasiCaps::STDIO_OUT);
+                match wasm::run_command(&bytes, ctx, opts.config) {
+                    Ok((code, ctx)) => {
+                        if ctx.denials() > 0 {
+                            crate::println!("wasm: {} call(s) denied by sandbox", ctx.denials());
+                        }
+                        CommandResult::Success(code)
+                    }
+                    Err(e) => CommandResult::Error(format!("wasm: {}", e)),
+                }
+            }
+            "info" => {
+                let Some(path) = args.get(1) else {
+                    return CommandResult::Error(String::from("wasm info: missing file"));
+                };
+                let module = match crate::fs::read_file(path)
+                    .map_err(|e| format!("{}", e))
+                    .and_then(|bytes| wasm::Module::decode(&bytes).map_err(|e| format!("{}", e)))
+                {
+                    Ok(module) => module,
+                    Err(e) => return CommandResult::Error(format!("wasm: {}: {}", path, e)),
+                };
+                crate::println!(
+                    "{}: {} functions, {} imports, {} exports",
+                    path,
+                    module.functions.len(),
+                    module.imports.len(),
+                    module.exports.len()
+                );
+                if let Some(memory) = module.memory {
+                    match memory.max {
+                        Some(max) => crate::println!("  memory: {}..{} pages", memory.min, max),
+                        None => crate::println!("  memory: {} pages", memory.min),
+                    }
+                }
+                for (idx, import) in module.imports.iter().enumerate() {
+                    let ty = module.func_type(idx as u32).cloned().unwrap_or_default();
+                    crate::println!("  import {}::{} {}", import.module, import.name, ty);
+                }
+                for export in &module.exports {
+                    match export.kind {
+                        wasm::module::ExportKind::Func => {
+                            let ty = module.func_type(export.index).cloned().unwrap_or_default();
+                            crate::println!("  export {} {}", export.name, ty);
+                        }
+                        kind => crate::println!("  export {} ({:?})", export.name, kind),
+                    }
+                }
+                CommandResult::Success(0)
+            }
+            "load" => {
+                let opts = match WasmOptions::parse(&args[1..]) {
+                    Ok(opts) => opts,
+                    Err(e) => return CommandResult::Error(format!("wasm: {}", e)),
+                };
+                let [name, path] = opts.rest.as_slice() else {
+                    return CommandResult::Error(String::from("wasm load: expected <name> <file>"));
+                };
+                let bytes = match crate::fs::read_file(path) {
+                    Ok(bytes) => bytes,
+                    Err(e) => return CommandResult::Error(format!("wasm: {}: {}", path, e)),
+                };
+                let ctx = opts.context(alloc::vec![name.clone()], wasm::WasiCaps::STDIO_OUT);
+                match wasm::load_extension(name, &bytes, ctx, opts.config) {
+                    Ok(()) => {
+                        crate::println!("Loaded extension '{}'", name);
+                        CommandResult::Success(0)
+                    }
+                    Err(e) => CommandResult::Error(format!("wasm: {}", e)),
+                }
+            }
+            "call" => {
+                let (Some(name), Some(export)) = (args.get(1), args.get(2)) else {
+                    return CommandResult::Error(String::from(
+                        "wasm call: expected <name> <export> [args...]",
+                    ));
+                };
+                let ty = match wasm::extension_signature(name, export) {
+                    Ok(ty) => ty,
+                    Err(e) => return CommandResult::Error(format!("wasm: {}", e)),
+                };
+                let params = &args[3..];
+                if params.len() != ty.params.len() {
+                    return CommandResult::Error(format!("wasm: {} expects {}", export, ty));
+                }
+                let mut values = Vec::new();
+                for (param, arg) in ty.params.iter().zip(params) {
+                    match wasm::Value::parse(*param, arg) {
+                        Some(value) => values.push(value),
+                        None => {
+                            return CommandResult::Error(format!(
+                                "wasm: invalid {} argument '{}'",
+                                param, arg
+                            ))
+                        }
+                    }
+                }
+                match wasm::call_extension(name, export, &values) {
+                    Ok((results, output)) => {
+                        crate::print!("{}", String::from_utf8_lossy(&output));
+                        let results: Vec<String> =
+                            results.iter().map(|v| format!("{}", v)).collect();
+                        if !results.is_empty() {
+                            crate::println!("{}", results.join(" "));
+                        }
+                        CommandResult::Success(0)
+                    }
+                    Err(e) => CommandResult::Error(format!("wasm: {}", e)),
+                }
+            }
+            "unload" => {
+                let Some(name) = args.get(1) else {
+                    return CommandResult::Error(String::from("wasm unload: missing name"));
+                };
+                if wasm::unload_extension(name) {
+                    CommandResult::Success(0)
+                } else {
+                    CommandResult::Error(format!("wasm: no extension '{}'", name))
+                }
+            }
+            "list" => {
+                for name in wasm::extensions() {
+                    crate::println!("{}", name);
+                }
+                CommandResult::Success(0)
+            }
+            other => {
+                crate::println!("wasm: unknown subcommand '{}'", other);
+                CommandResult::Success(1)
+            }
+        }
+    }
+}
+
+/// Sandbox options shared by `wasm run` and `wasm load`
+struct WasmOptions {
+    config: crate::wasm::Config,
+    caps: crate::wasm::WasiCaps,
+    dirs: Vec<(String, String, crate::cap::Rights)>,
+    env: Vec<(String, String)>,
+    /// Positional arguments after the options
+    rest: Vec<String>,
+}
+
+impl WasmOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        use crate::{cap::Rights, wasm};
+
+        let mut opts = WasmOptions {
+            config: wasm::Config {
+                fuel: Some(wasm::DEFAULT_FUEL),
+                ..wasm::Config::default()
+            },
+            caps: wasm::WasiCaps::NONE,
+            dirs: Vec::new(),
+            env: Vec::new(),
+            rest: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                opts.rest.push(arg.clone());
+                opts.rest.extend(iter.cloned());
+                break;
+            }
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("{} requires a value", arg))?;
+            match arg.as_str() {
+                "--fuel" => {
+                    opts.config.fuel = match value.as_str() {
+                        "none" => None,
+                        n => Some(n.parse().map_err(|_| format!("invalid fuel '{}'", n))?),
+                    }
+                }
+                "--mem" => {
+                    opts.config.max_pages = value
+                        .parse()
+                        .map_err(|_| format!("invalid page count '{}'", value))?
+                }
+                "--dir" => {
+                    let mut parts = value.split(':');
+                    let guest = parts.next().unwrap_or_default();
+                    let mut host = guest;
+                    let mut rights = Rights::READ.union(Rights::WRITE).union(Rights::CREATE);
+                    for part in parts {
+                        if part == "ro" {
+                            rights = Rights::READ;
+                        } else {
+                            host = part;
+                        }
+                    }
+                    if guest.is_empty() {
+                        return Err(String::from("--dir requires a directory"));
+                    }
+                    opts.dirs
+                        .push((String::from(guest), String::from(host), rights));
+                }
+                "--env" => {
+                    let (key, val) = value
+                        .split_once('=')
+                        .ok_or_else(|| format!("invalid environment entry '{}'", value))?;
+                    opts.env.push((String::from(key), String::from(val)));
+                }
+                "--allow" => {
+                    opts.caps = opts.caps
+                        | wasm::WasiCaps::parse(value)
+                            .ok_or_else(|| format!("unknown capability in '{}'", value))?;
+                }
+                other => return Err(format!("unknown option '{}'", other)),
+            }
+        }
+        Ok(opts)
+    }
+
+    fn context(&self, argv: Vec<String>, base: crate::wasm::WasiCaps) -> crate::wasm::WasiCtx {
+        let mut ctx = crate::wasm::WasiCtx::new(argv).grant(base | self.caps);
+        for (key, val) in &self.env {
+            ctx = ctx.env(key, val);
+        }
+        for (guest, host, rights) in &self.dirs {
+            ctx = ctx.preopen_dir(guest, host, *rights);
+        }
+        ctx
+    }
+}
@@ -4,7 +4,7 @@
 
 #![allow(unused_variables, unused_assignments)]
 
-use alloc::{format, string::String, vec::Vec};
+use alloc::{format, string::String, sync::Arc, vec::Vec};
 
 use super::read_file_to_string;
 use crate::services::shell::{BuiltinCommand, CommandResult, Shell};
@@ -390,10 +390,33 @@ impl BuiltinCommand for MountCommand {
         "mount"
     }
     fn description(&self) -> &str {
-        "Show mounted filesystems"
+        "Show mounted filesystems, or mount a block device or 9p export"
     }
 
-    fn execute(&self, _args: &[String], _shell: &Shell) -> CommandResult {
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        if !args.is_empty() {
+            if args.len() < 2 {
+                return CommandResult::Error(String::from(
+                    "Usage: mount [<device|tag> <dir> [blockfs|9p]]",
+                ));
+            }
+            let fs_type = args.get(2).map_or("blockfs", |s| s.as_str());
+            let Some(vfs) = crate::fs::try_get_vfs() else {
+                return CommandResult::Error(String::from("mount: VFS not initialized"));
+            };
+            let mounted = match fs_type {
+                "9p" => vfs.write().mount_9p(&args[1], &args[0]),
+                _ => vfs.write().mount_block_device(&args[1], fs_type, &args[0]),
+            };
+            return match mounted {
+                Ok(()) => CommandResult::Success(0),
+                Err(e) => CommandResult::Error(format!(
+                    "mount: cannot mount {} on {}: {:?}",
+                    args[0], args[1], e
+                )),
+            };
+        }
+
         if let Some(vfs) = crate::fs::try_get_vfs() {
             let vfs_guard = vfs.read();
             let mounts = vfs_guard.list_mounts();
@@ -1161,18 +1184,13 @@ impl BuiltinCommand for XattrCommand {
             ));
         }
 
-        // Resolve path to inode via VFS metadata
-        let resolve_inode = |path: &str| -> Result<u64, String> {
+        // Resolve path to its VFS node (which owns the attribute storage)
+        let resolve_node = |path: &str| -> Result<Arc<dyn crate::fs::VfsNode>, String> {
             let vfs_lock = crate::fs::try_get_vfs()
                 .ok_or_else(|| String::from("xattr: VFS not initialized"))?;
             let vfs = vfs_lock.read();
-            let node = vfs
-                .resolve_path(path)
-                .map_err(|e| format!("xattr: cannot resolve '{}': {:?}", path, e))?;
-            let meta = node
-                .metadata()
-                .map_err(|e| format!("xattr: metadata error: {:?}", e))?;
-            Ok(meta.inode)
+            vfs.resolve_path(path)
+                .map_err(|e| format!("xattr: cannot resolve '{}': {:?}", path, e))
         };
 
         match args[0].as_str() {
@@ -1182,11 +1200,11 @@ impl BuiltinCommand for XattrCommand {
                         "Usage: xattr list|get|set|remove <path> [name] [value]",
                     ));
                 }
-                let inode = match resolve_inode(&args[1]) {
-                    Ok(i) => i,
+                let node = match resolve_node(&args[1]) {
+                    Ok(n) => n,
                     Err(e) => return CommandResult::Error(e),
                 };
-                match crate::fs::xattr::listxattr(inode) {
+                match node.list_xattr() {
                     Ok(attrs) if attrs.is_empty() => {
                         crate::println!("Extended attributes for {}: (none)", args[1]);
                     }
@@ -1208,11 +1226,11 @@ impl BuiltinCommand for XattrCommand {
                         "Usage: xattr list|get|set|remove <path> [name] [value]",
                     ));
                 }
-                let inode = match resolve_inode(&args[1]) {
-                    Ok(i) => i,
+                let node = match resolve_node(&args[1]) {
+                    Ok(n) => n,
                     Err(e) => return CommandResult::Error(e),
                 };
-                match crate::fs::xattr::getxattr(inode, &args[2]) {
+                match node.get_xattr(&args[2]) {
                     Ok(value) => {
                         // Try to display as UTF-8, fall back to hex
                         if let Ok(s) = core::str::from_utf8(&value) {
@@ -1237,11 +1255,11 @@ impl BuiltinCommand for XattrCommand {
                         "Usage: xattr list|get|set|remove <path> [name] [value]",
                     ));
                 }
-                let inode = match resolve_inode(&args[1]) {
-                    Ok(i) => i,
+                let node = match resolve_node(&args[1]) {
+                    Ok(n) => n,
                     Err(e) => return CommandResult::Error(e),
                 };
-                match crate::fs::xattr::setxattr(inode, &args[2], args[3].as_bytes(), 0) {
+                match node.set_xattr(&args[2], args[3].as_bytes(), 0) {
                     Ok(()) => {
                         crate::println!("Set {}={} on {}", args[2], args[3], args[1]);
                     }
@@ -1257,11 +1275,11 @@ impl BuiltinCommand for XattrCommand {
                         "Usage: xattr list|get|set|remove <path> [name] [value]",
                     ));
                 }
-                let inode = match resolve_inode(&args[1]) {
-                    Ok(i) => i,
+                let node = match resolve_node(&args[1]) {
+                    Ok(n) => n,
                     Err(e) => return CommandResult::Error(e),
                 };
-                match crate::fs::xattr::removexattr(inode, &args[2]) {
+                match node.remove_xattr(&args[2]) {
                     Ok(()) => {
                         crate::println!("Removed {} from {}", args[2], args[1]);
                     }
@@ -1400,6 +1418,60 @@ impl BuiltinCommand for MkfsCommand {
     }
 }
 
+pub(in crate::services::shell) struct LosetupCommand;
+impl BuiltinCommand for LosetupCommand {
+    fn name(&self) -> &str {
+        "losetup"
+    }
+    fn description(&self) -> &str {
+        "Set up and control loop devices"
+    }
+
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        match args.first().map(String::as_str) {
+            None => {
+                for name in crate::drivers::block::names() {
+                    if let Some(dev) = crate::drivers::block::get(&name) {
+                        let dev = dev.lock();
+                        crate::println!(
+                            "/dev/{}: {} blocks of {} bytes{}",
+                            name,
+                            dev.block_count(),
+                            dev.block_size(),
+                            if dev.is_read_only() { " (ro)" } else { "" }
+                        );
+                    }
+                }
+                CommandResult::Success(0)
+            }
+            Some("-d") if args.len() == 2 => {
+                match crate::drivers::block::loop_dev::detach(&args[1]) {
+                    Ok(()) => CommandResult::Success(0),
+                    Err(e) => CommandResult::Error(format!("losetup: {}: {:?}", args[1], e)),
+                }
+            }
+            Some(_) => {
+                let (read_only, file) = match args {
+                    [flag, file] if flag == "-r" => (true, file),
+                    [file] => (false, file),
+                    _ => {
+                        return CommandResult::Error(String::from(
+                            "Usage: losetup [-r] <file> | losetup -d <device>",
+                        ))
+                    }
+                };
+                match crate::drivers::block::loop_dev::attach(file, read_only) {
+                    Ok(name) => {
+                        crate::println!("/dev/{}", name);
+                        CommandResult::Success(0)
+                    }
+                    Err(e) => CommandResult::Error(format!("losetup: {}: {:?}", file, e)),
+                }
+            }
+        }
+    }
+}
+
 pub(in crate::services::shell) struct FsckCommand;
 impl BuiltinCommand for FsckCommand {
     fn name(&self) -> &str {
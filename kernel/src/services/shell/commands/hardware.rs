@@ -127,6 +127,27 @@ impl BuiltinCommand for LsblkCommand {
                 idx += 1;
             }
         }
+        drop(bus);
+        for name in crate::drivers::block::names() {
+            if let Some(dev) = crate::drivers::block::get(&name) {
+                let dev = dev.lock();
+                let _mib = dev.block_count() * dev.block_size() as u64 / (1024 * 1024);
+                let dev_type = if name.starts_with("loop") {
+                    "loop"
+                } else if name.starts_with("ram") {
+                    "ram"
+                } else {
+                    "null"
+                };
+                crate::println!(
+                    "{:<10} {:<8} {:<12} -",
+                    name,
+                    dev_type,
+                    format!("{}M", _mib)
+                );
+                idx += 1;
+            }
+        }
         if idx == 0 {
             crate::println!("(no block devices found)");
         }
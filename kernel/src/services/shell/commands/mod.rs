@@ -17,7 +17,9 @@ mod desktop;
 mod devtools;
 mod filesystem;
 mod hardware;
+#[cfg(feature = "net")]
 mod network;
+#[cfg(feature = "pkg")]
 mod package;
 mod security;
 mod system;
@@ -34,7 +36,9 @@ pub(super) use desktop::*;
 pub(super) use devtools::*;
 pub(super) use filesystem::*;
 pub(super) use hardware::*;
+#[cfg(feature = "net")]
 pub(super) use network::*;
+#[cfg(feature = "pkg")]
 pub(super) use package::*;
 pub(super) use security::*;
 pub(super) use system::*;
@@ -118,6 +122,7 @@ pub(super) fn parse_i64(s: &str) -> i64 {
 /// Parse a simple IPv6 address string (colon-hex notation).
 ///
 /// Supports full notation (8 groups) and compressed :: notation.
+#[cfg(feature = "net")]
 pub(super) fn parse_ipv6_address(s: &str) -> Option<crate::net::Ipv6Address> {
     // Handle special cases
     if s == "::1" {
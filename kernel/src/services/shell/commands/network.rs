@@ -21,89 +21,98 @@ impl BuiltinCommand for IfconfigCommand {
     }
 
     fn execute(&self, _args: &[String], _shell: &Shell) -> CommandResult {
-        let devices = crate::net::device::list_devices();
-        if devices.is_empty() {
+        use crate::net::device::{IFF_LOOPBACK, IFF_RUNNING, IFF_UP};
+
+        let interfaces = crate::net::device::interfaces();
+        if interfaces.is_empty() {
             crate::println!("No network interfaces found.");
             return CommandResult::Success(0);
         }
 
-        for dev_name in &devices {
-            crate::net::device::with_device(dev_name, |dev| {
-                let mac = dev.mac_address();
-                let state = dev.state();
-                let stats = dev.statistics();
-                let caps = dev.capabilities();
+        for iface in &interfaces {
+            let mac = iface.mac;
+            let stats = iface.stats;
+            let mut flags = String::new();
+            for (bit, name) in [
+                (IFF_UP, "UP"),
+                (IFF_LOOPBACK, "LOOPBACK"),
+                (IFF_RUNNING, "RUNNING"),
+            ] {
+                if iface.flags & bit != 0 {
+                    if !flags.is_empty() {
+                        flags.push(',');
+                    }
+                    flags.push_str(name);
+                }
+            }
 
+            crate::println!(
+                "{}: flags={}<{}> mtu {}",
+                iface.name,
+                iface.flags,
+                flags,
+                iface.mtu
+            );
+            crate::println!(
+                "        ether {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+                mac.0[0],
+                mac.0[1],
+                mac.0[2],
+                mac.0[3],
+                mac.0[4],
+                mac.0[5]
+            );
+
+            if let Some((ip, mask)) = crate::net::ip::interface_address(&iface.name) {
                 crate::println!(
-                    "{}: flags=<{:?}> mtu {}",
-                    dev.name(),
-                    state,
-                    caps.max_transmission_unit
-                );
-                crate::println!(
-                    "        ether {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
-                    mac.0[0],
-                    mac.0[1],
-                    mac.0[2],
-                    mac.0[3],
-                    mac.0[4],
-                    mac.0[5]
+                    "        inet {}.{}.{}.{} netmask {}.{}.{}.{}",
+                    ip.0[0],
+                    ip.0[1],
+                    ip.0[2],
+                    ip.0[3],
+                    mask.0[0],
+                    mask.0[1],
+                    mask.0[2],
+                    mask.0[3],
                 );
+            }
 
-                // Show IP config for non-loopback interfaces
-                if dev.name() != "lo0" {
-                    let config = crate::net::ip::get_interface_config();
-                    let ip = config.ip_addr;
-                    let mask = config.subnet_mask;
-                    crate::println!(
-                        "        inet {}.{}.{}.{} netmask {}.{}.{}.{}",
-                        ip.0[0],
-                        ip.0[1],
-                        ip.0[2],
-                        ip.0[3],
-                        mask.0[0],
-                        mask.0[1],
-                        mask.0[2],
-                        mask.0[3],
-                    );
-
-                    // Show IPv6 addresses
-                    if let Some(v6_config) = crate::net::ipv6::get_config() {
-                        for addr_info in &v6_config.ipv6_addresses {
-                            let scope_str = match addr_info.scope {
-                                crate::net::ipv6::Ipv6Scope::LinkLocal => "link",
-                                crate::net::ipv6::Ipv6Scope::Global => "global",
-                                crate::net::ipv6::Ipv6Scope::SiteLocal => "site",
-                            };
-                            crate::println!(
-                                "        inet6 {}  prefixlen {}  scopeid <{}>",
-                                crate::net::ipv6::format_ipv6_compressed(&addr_info.address),
-                                addr_info.prefix_len,
-                                scope_str,
-                            );
-                        }
+            if iface.flags & IFF_LOOPBACK == 0 {
+                // Show IPv6 addresses
+                if let Some(v6_config) = crate::net::ipv6::get_config() {
+                    for addr_info in &v6_config.ipv6_addresses {
+                        let scope_str = match addr_info.scope {
+                            crate::net::ipv6::Ipv6Scope::LinkLocal => "link",
+                            crate::net::ipv6::Ipv6Scope::Global => "global",
+                            crate::net::ipv6::Ipv6Scope::SiteLocal => "site",
+                        };
+                        crate::println!(
+                            "        inet6 {}  prefixlen {}  scopeid <{}>",
+                            crate::net::ipv6::format_ipv6_compressed(&addr_info.address),
+                            addr_info.prefix_len,
+                            scope_str,
+                        );
                     }
-                } else {
-                    crate::println!("        inet 127.0.0.1 netmask 255.0.0.0");
-                    crate::println!("        inet6 ::1  prefixlen 128  scopeid <host>");
                 }
+            } else {
+                crate::println!("        inet6 ::1  prefixlen 128  scopeid <host>");
+            }
 
-                crate::println!(
-                    "        RX packets {} bytes {}  errors {} dropped {}",
-                    stats.rx_packets,
-                    stats.rx_bytes,
-                    stats.rx_errors,
-                    stats.rx_dropped
-                );
-                crate::println!(
-                    "        TX packets {} bytes {}  errors {} dropped {}",
-                    stats.tx_packets,
-                    stats.tx_bytes,
-                    stats.tx_errors,
-                    stats.tx_dropped
-                );
-                crate::println!();
-            });
+            crate::println!(
+                "        RX packets {} bytes {}  errors {} dropped {}",
+                stats.rx_packets,
+                stats.rx_bytes,
+                stats.rx_errors,
+                stats.rx_dropped
+            );
+            crate::println!(
+                "        TX packets {} bytes {}  errors {} dropped {}",
+                stats.tx_packets,
+                stats.tx_bytes,
+                stats.tx_errors,
+                stats.tx_dropped
+            );
+            crate::println!();
         }
 
         CommandResult::Success(0)
@@ -712,6 +721,74 @@ impl BuiltinCommand for NtpCommand {
     }
 }
 
+#[cfg(all(feature = "net", feature = "pkg"))]
+pub(in crate::services::shell) struct SyslogCommand;
+#[cfg(all(feature = "net", feature = "pkg"))]
+impl BuiltinCommand for SyslogCommand {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+    fn description(&self) -> &str {
+        "Remote syslog forwarding"
+    }
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::log_service::syslog;
+
+        match args.first().map(String::as_str) {
+            None | Some("status") => match (syslog::target(), syslog::stats()) {
+                (Some((server, transport)), Some(stats)) => {
+                    let transport = match transport {
+                        syslog::Transport::Udp => "udp",
+                        syslog::Transport::Tcp => "tcp",
+                    };
+                    if let crate::net::IpAddress::V4(ip) = server.ip() {
+                        crate::println!(
+                            "Syslog: forwarding to {}.{}.{}.{}:{} ({})",
+                            ip.0[0],
+                            ip.0[1],
+                            ip.0[2],
+                            ip.0[3],
+                            server.port(),
+                            transport
+                        );
+                    }
+                    crate::println!(
+                        "  Sent: {}  Spooled: {}  Dropped: {}",
+                        stats.sent,
+                        stats.spooled,
+                        stats.dropped
+                    );
+                }
+                _ => crate::println!("Syslog: forwarding disabled"),
+            },
+            Some("flush") => match syslog::flush() {
+                Some(0) => crate::println!("Syslog spool flushed"),
+                Some(n) => crate::println!("Syslog: {} messages still spooled", n),
+                None => crate::println!("Syslog: forwarding disabled"),
+            },
+            Some("reload") => {
+                let path = args.get(1).map_or(syslog::CONFIG_PATH, String::as_str);
+                match syslog::load_config(path) {
+                    Ok(()) => crate::println!("Syslog configuration loaded from {}", path),
+                    Err(e) => {
+                        crate::println!("syslog: {}: {:?}", path, e);
+                        return CommandResult::Success(1);
+                    }
+                }
+            }
+            Some("disable") => {
+                syslog::disable();
+                crate::println!("Syslog forwarding disabled");
+            }
+            Some(_) => {
+                crate::println!("Usage: syslog status|flush|reload [path]|disable");
+                return CommandResult::Success(1);
+            }
+        }
+        CommandResult::Success(0)
+    }
+}
+
 pub(in crate::services::shell) struct VpnCommand;
 impl BuiltinCommand for VpnCommand {
     fn name(&self) -> &str {
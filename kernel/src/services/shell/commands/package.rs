@@ -2,7 +2,7 @@
 
 #![allow(unused_variables, unused_assignments)]
 
-use alloc::{format, string::String};
+use alloc::{format, string::String, vec::Vec};
 
 use crate::services::shell::{BuiltinCommand, CommandResult, Shell};
 
@@ -12,20 +12,28 @@ impl BuiltinCommand for PkgCommand {
         "pkg"
     }
     fn description(&self) -> &str {
-        "Package management (install, remove, update, upgrade, list, search, info, verify)"
+        "Package management (fetch, install, remove, update, upgrade, list, search, info, verify)"
     }
 
     fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
         if args.is_empty() {
             return CommandResult::Error(String::from(
-                "Usage: pkg <install|remove|update|upgrade|list|search|info|verify> [args...]",
+                "Usage: pkg <fetch|install|remove|update|upgrade|list|search|info|verify> \
+                 [args...]",
             ));
         }
 
         let subcommand = args[0].as_str();
         let sub_args = &args[1..];
 
+        if crate::pkg::pkgd_client::is_available() {
+            if let Some(result) = pkgd_request(subcommand, sub_args) {
+                return result;
+            }
+        }
+
         match subcommand {
+            "fetch" => CommandResult::Error(String::from("pkg fetch: pkgd is not running")),
             "install" => pkg_install(sub_args),
             "remove" => pkg_remove(sub_args),
             "update" => pkg_update(),
@@ -39,6 +47,49 @@ impl BuiltinCommand for PkgCommand {
     }
 }
 
+/// Forward a subcommand to the user-space package service.
+///
+/// Returns `None` for subcommands `pkgd` does not handle; those keep using
+/// the in-kernel package manager.
+fn pkgd_request(subcommand: &str, args: &[String]) -> Option<CommandResult> {
+    use crate::pkg::pkgd_client::{
+        request, PKGD_OP_FETCH, PKGD_OP_INSTALL, PKGD_OP_LIST, PKGD_OP_REMOVE, PKGD_OP_UPGRADE,
+        PKGD_OP_VERIFY,
+    };
+
+    let op = match subcommand {
+        "fetch" => PKGD_OP_FETCH,
+        "install" => PKGD_OP_INSTALL,
+        "remove" => PKGD_OP_REMOVE,
+        "upgrade" => PKGD_OP_UPGRADE,
+        "list" if !args.iter().any(|a| a == "--available") => PKGD_OP_LIST,
+        "verify" => PKGD_OP_VERIFY,
+        _ => return None,
+    };
+
+    // pkgd upgrades/lists everything when given no names
+    let args: Vec<&str> = args
+        .iter()
+        .map(|a| a.as_str())
+        .filter(|a| *a != "--all" && *a != "--installed")
+        .collect();
+    if matches!(op, PKGD_OP_FETCH | PKGD_OP_INSTALL | PKGD_OP_REMOVE) && args.is_empty() {
+        return Some(CommandResult::Error(format!(
+            "Usage: pkg {} <package>...",
+            subcommand
+        )));
+    }
+
+    Some(match request(op, &args) {
+        Ok(reply) if reply.status == 0 => CommandResult::Success(0),
+        Ok(reply) => CommandResult::Error(format!(
+            "pkg {}: failed (errno {})",
+            subcommand, -reply.status
+        )),
+        Err(e) => CommandResult::Error(format!("pkg {}: {}", subcommand, e)),
+    })
+}
+
 /// Install a package by name
 fn pkg_install(args: &[String]) -> CommandResult {
     if args.is_empty() {
@@ -1,4 +1,4 @@
-//! Security subsystem commands (capabilities, MAC, audit, TPM).
+//! Security subsystem commands (capabilities, MAC, audit, sandbox, TPM).
 
 #![allow(unused_variables, unused_assignments)]
 
@@ -153,6 +153,79 @@ impl BuiltinCommand for AuditCommand {
     }
 }
 
+pub(in crate::services::shell) struct SandboxCommand;
+impl BuiltinCommand for SandboxCommand {
+    fn name(&self) -> &str {
+        "sandbox"
+    }
+    fn description(&self) -> &str {
+        "Application sandbox profiles and syscall filters"
+    }
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::security::sandbox;
+
+        let Some(arg) = args.first() else {
+            let profiles = sandbox::load_profiles();
+            if profiles.is_empty() {
+                crate::println!("No profiles in {}", sandbox::PROFILE_DIR);
+            }
+            let mut broken = 0;
+            for (name, parsed) in profiles {
+                match parsed {
+                    Ok(profile) => crate::println!(
+                        "{:<20} {:<8} {}",
+                        name,
+                        profile.network.as_str(),
+                        profile.programs.join(" ")
+                    ),
+                    Err(e) => {
+                        broken += 1;
+                        crate::println!("{:<20} INVALID: {:?}", name, e);
+                    }
+                }
+            }
+            return if broken == 0 {
+                CommandResult::Success(0)
+            } else {
+                CommandResult::Error(String::from("invalid profiles"))
+            };
+        };
+
+        let Some(process) = arg
+            .parse()
+            .ok()
+            .and_then(|pid| crate::process::get_process(crate::process::ProcessId(pid)))
+        else {
+            crate::println!("Usage: sandbox [pid]");
+            return CommandResult::Error(String::from("no such process"));
+        };
+        match process.sandbox.lock().clone() {
+            None => crate::println!("PID {}: unconfined", process.pid.0),
+            Some(profile) => {
+                crate::println!("PID {}: profile {}", process.pid.0, profile.name);
+                crate::println!("Programs: {}", profile.programs.join(" "));
+                crate::println!("Read:     {}", profile.read.join(" "));
+                crate::println!("Write:    {}", profile.write.join(" "));
+                crate::println!("Services: {}", profile.services.join(" "));
+                crate::println!("Network:  {}", profile.network.as_str());
+            }
+        }
+        let filters = process.syscall_filters.lock().clone();
+        let active = filters.active.iter().map(|f| (f, ""));
+        let pending = filters.on_exec.iter().map(|f| (f, ", from next exec"));
+        for (filter, when) in active.chain(pending) {
+            crate::println!(
+                "Filter:   {} {} syscalls, {}{}",
+                filter.mode_str(),
+                filter.len(),
+                filter.action.as_str(),
+                when
+            );
+        }
+        CommandResult::Success(0)
+    }
+}
+
 pub(in crate::services::shell) struct TpmCommand;
 impl BuiltinCommand for TpmCommand {
     fn name(&self) -> &str {
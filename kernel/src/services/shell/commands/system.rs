@@ -129,6 +129,7 @@ impl BuiltinCommand for HelpCommand {
                     "nat",
                     "dns",
                     "ntp",
+                    "syslog",
                     "vpn",
                     "wg",
                     "wifi",
@@ -144,7 +145,7 @@ impl BuiltinCommand for HelpCommand {
                     "klist",
                 ],
             ),
-            ("Security", &["audit", "cap", "mac", "tpm"]),
+            ("Security", &["audit", "cap", "mac", "sandbox", "tpm"]),
             ("Crypto", &["blake3sum", "sha256sum"]),
             (
                 "Hardware",
@@ -154,7 +155,9 @@ impl BuiltinCommand for HelpCommand {
             ),
             (
                 "Performance",
-                &["perf", "trace", "sched", "slab", "vmstat", "numa", "kpti"],
+                &[
+                    "perf", "trace", "sched", "slab", "memprof", "vmstat", "numa", "kpti",
+                ],
             ),
             (
                 "DevTools",
@@ -167,6 +170,7 @@ impl BuiltinCommand for HelpCommand {
                     "winfo",
                     "browser",
                     "screenshot",
+                    "inputrec",
                     "notify",
                     "theme",
                 ],
@@ -295,7 +299,7 @@ impl BuiltinCommand for PsCommand {
         let process_server = crate::services::process_server::get_process_server();
         let processes = process_server.list_processes();
 
-        crate::println!("  PID  PPID STATE    NAME");
+        crate::println!("  PID  PPID STATE     %CPU     TIME NAME");
         for process in processes {
             let state = match process.state {
                 crate::services::process_server::ProcessState::Running => "RUN",
@@ -306,11 +310,15 @@ impl BuiltinCommand for PsCommand {
                 crate::services::process_server::ProcessState::Dead => "DEAD",
             };
 
+            let secs = process.cpu_time / 1_000_000;
             crate::println!(
-                "{:5} {:5} {:8} {}",
+                "{:5} {:5} {:8} {:>5} {:>5}:{:02} {}",
                 process.pid.0,
                 process.ppid.0,
                 state,
+                format_cpu_percent(process.cpu_permille()),
+                secs / 60,
+                secs % 60,
                 process.name
             );
         }
@@ -319,6 +327,11 @@ impl BuiltinCommand for PsCommand {
     }
 }
 
+/// A CPU share in tenths of a percent, as `12.3`.
+fn format_cpu_percent(permille: u64) -> String {
+    format!("{}.{}", permille / 10, permille % 10)
+}
+
 pub(in crate::services::shell) struct KillCommand;
 impl BuiltinCommand for KillCommand {
     fn name(&self) -> &str {
@@ -392,6 +405,11 @@ impl BuiltinCommand for LsmodCommand {
         crate::println!("  Total devices: {}", stats.total_devices);
         crate::println!("  Bound devices: {}", stats.bound_devices);
         crate::println!("  Active devices: {}", stats.active_devices);
+        crate::println!("  Suspended devices: {}", stats.suspended_devices);
+        crate::println!(
+            "  Runtime-suspended devices: {}",
+            stats.runtime_suspended_devices
+        );
 
         CommandResult::Success(0)
     }
@@ -553,6 +571,7 @@ impl BuiltinCommand for UnameCommand {
         let show_sysname = args.is_empty() || show_all || args.iter().any(|a| a == "-s");
         let show_nodename = show_all || args.iter().any(|a| a == "-n");
         let show_release = show_all || args.iter().any(|a| a == "-r");
+        let show_version = show_all || args.iter().any(|a| a == "-v");
         let show_machine = show_all || args.iter().any(|a| a == "-m");
 
         let mut parts: Vec<&str> = Vec::new();
@@ -566,6 +585,9 @@ impl BuiltinCommand for UnameCommand {
         if show_release {
             parts.push("0.25.2");
         }
+        if show_version {
+            parts.push(crate::utils::version::UNAME_VERSION);
+        }
         if show_machine {
             #[cfg(target_arch = "x86_64")]
             parts.push("x86_64");
@@ -633,42 +655,40 @@ impl BuiltinCommand for DmesgCommand {
         "Show kernel message buffer"
     }
 
-    fn execute(&self, _args: &[String], _shell: &Shell) -> CommandResult {
-        // Try reading boot log from VFS first
-        if let Some(vfs_lock) = crate::fs::try_get_vfs() {
-            let vfs = vfs_lock.read();
-            if let Ok(node) = vfs.resolve_path("/var/log/boot.log") {
-                let mut buffer = [0u8; 4096];
-                let mut offset = 0usize;
-                loop {
-                    match node.read(offset, &mut buffer) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            if let Ok(text) = core::str::from_utf8(&buffer[..n]) {
-                                crate::print!("{}", text);
-                            }
-                            offset += n;
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::log_service::{self, LogLevel};
+
+        let mut max_level = LogLevel::Trace;
+        let mut clear = false;
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "-c" => clear = true,
+                "-l" if i + 1 < args.len() => {
+                    i += 1;
+                    match LogLevel::parse(&args[i]) {
+                        Some(level) => max_level = level,
+                        None => {
+                            return CommandResult::Error(format!(
+                                "dmesg: unknown level '{}'",
+                                args[i]
+                            ))
                         }
-                        Err(_) => break,
                     }
                 }
-                return CommandResult::Success(0);
+                _ => return CommandResult::Error(String::from("usage: dmesg [-c] [-l level]")),
             }
+            i += 1;
         }
 
-        // Fall back: show init system services if available
-        if let Some(init) = crate::services::init_system::try_get_init_system() {
-            let services = init.list_services();
-            crate::println!("[dmesg] Boot log not available. Registered services:");
-            for svc in &services {
-                crate::println!("  [init] {} ({:?})", svc.definition.name, svc.state);
-            }
-            return CommandResult::Success(0);
+        crate::print!("{}", log_service::log_read(max_level));
+        let dropped = log_service::log_dropped();
+        if dropped > 0 {
+            crate::println!("[dmesg] {} messages dropped", dropped);
+        }
+        if clear {
+            log_service::log_clear();
         }
-
-        // Final fallback: kernel version info
-        crate::println!("[dmesg] VeridianOS v0.18.0 -- kernel ring buffer not yet populated");
-        crate::println!("[dmesg] Use serial capture (QEMU -serial file:log) to review boot log.");
         CommandResult::Success(0)
     }
 }
@@ -1184,6 +1204,115 @@ impl BuiltinCommand for PerfCommand {
     }
 }
 
+pub(in crate::services::shell) struct BenchCommand;
+impl BuiltinCommand for BenchCommand {
+    fn name(&self) -> &str {
+        "bench"
+    }
+    fn description(&self) -> &str {
+        "Run subsystem benchmarks (bench fs|ipc)"
+    }
+
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::bench::fs::{FsBenchConfig, Target};
+
+        const USAGE: &str = concat!(
+            "Usage: bench fs [-d dir] [-s size_kib] [-b io_bytes] [-n files] [-r readers]\n",
+            "       bench ipc [-i iterations] [-b bulk_bytes] [-l load_endpoints]"
+        );
+
+        match args.first().map(String::as_str) {
+            Some("fs") => {}
+            Some("ipc") => return bench_ipc(&args[1..], USAGE),
+            _ => return CommandResult::Error(String::from(USAGE)),
+        }
+
+        let mut target = Target::Scratch;
+        let mut config = FsBenchConfig::default();
+        let mut rest = args[1..].iter();
+        while let Some(flag) = rest.next() {
+            let Some(value) = rest.next() else {
+                return CommandResult::Error(String::from(USAGE));
+            };
+            let number = value.parse::<usize>().ok().filter(|&n| n > 0);
+            match (flag.as_str(), number) {
+                ("-d", _) => target = Target::Directory(value.clone()),
+                ("-s", Some(kib)) => config.file_size = kib * 1024,
+                ("-b", Some(bytes)) => config.io_size = bytes,
+                ("-n", Some(files)) => config.metadata_files = files,
+                ("-r", Some(readers)) => config.readers = readers,
+                _ => return CommandResult::Error(String::from(USAGE)),
+            }
+        }
+        config.random_ops = (config.file_size / config.io_size).clamp(1, 4096);
+
+        crate::println!(
+            "bench fs: {} KiB file, {} byte I/O, {} files, {} readers on {}",
+            config.file_size / 1024,
+            config.io_size,
+            config.metadata_files,
+            config.readers,
+            match &target {
+                Target::Scratch => "a scratch BlockFS",
+                Target::Directory(dir) => dir.as_str(),
+            }
+        );
+        match crate::bench::fs::run(&target, &config) {
+            Ok(results) => {
+                crate::bench::fs::print_results(&results);
+                CommandResult::Success(0)
+            }
+            Err(e) => CommandResult::Error(format!("bench fs: {:?}", e)),
+        }
+    }
+}
+
+fn bench_ipc(args: &[String], usage: &str) -> CommandResult {
+    use crate::bench::ipc::{check, IpcBenchConfig, BASELINES};
+
+    let mut config = IpcBenchConfig::default();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let number = rest
+            .next()
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|&n| n > 0);
+        match (flag.as_str(), number) {
+            ("-i", Some(iterations)) => {
+                config.iterations = iterations as u64;
+                config.warmup = (iterations as u64 / 10).max(1);
+            }
+            ("-b", Some(bytes)) => config.bulk_size = bytes,
+            ("-l", Some(endpoints)) => config.load_endpoints = endpoints,
+            _ => return CommandResult::Error(String::from(usage)),
+        }
+    }
+
+    crate::println!(
+        "bench ipc: {} iterations, {} byte bulk transfers, {} load endpoints",
+        config.iterations,
+        config.bulk_size,
+        config.load_endpoints
+    );
+    let results = match crate::bench::ipc::run(&config) {
+        Ok(results) => results,
+        Err(e) => return CommandResult::Error(format!("bench ipc: {:?}", e)),
+    };
+    crate::bench::ipc::print_results(&results);
+
+    let regressions = check(&results, BASELINES);
+    for r in &regressions {
+        crate::println!(
+            "REGRESSION {}: {} = {} (limit {})",
+            r.name,
+            r.metric,
+            r.measured,
+            r.limit
+        );
+    }
+    CommandResult::Success(if regressions.is_empty() { 0 } else { 1 })
+}
+
 pub(in crate::services::shell) struct TraceCommand;
 impl BuiltinCommand for TraceCommand {
     fn name(&self) -> &str {
@@ -1195,11 +1324,15 @@ impl BuiltinCommand for TraceCommand {
 
     fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
         if args.len() < 2 {
-            crate::println!("Usage: trace <on|off|dump|status>");
+            crate::println!("Usage: trace <on|off|dump|status|stats|clear|events|pid>");
             crate::println!("  on    - Enable kernel tracing");
             crate::println!("  off   - Disable kernel tracing");
             crate::println!("  dump  - Dump trace buffer (last 32 events)");
             crate::println!("  status - Show tracing status");
+            crate::println!("  stats - Show per-event counters");
+            crate::println!("  clear - Drop buffered events and counters");
+            crate::println!("  events <all|name,...> - Record only these events");
+            crate::println!("  pid <pid|all> - Record only while a process runs");
             return CommandResult::Success(0);
         }
 
@@ -1228,6 +1361,62 @@ impl BuiltinCommand for TraceCommand {
                     if enabled { "enabled" } else { "disabled" },
                     total
                 );
+                crate::println!(
+                    "Event mask: {:#x}, PID filter: {}",
+                    crate::perf::trace::event_mask(),
+                    crate::perf::trace::pid_filter()
+                );
+            }
+            "stats" => {
+                let stats = crate::perf::trace::stats();
+                crate::println!(
+                    "{} events recorded, {} overwritten",
+                    stats.recorded,
+                    stats.overwritten
+                );
+                for (t, count) in stats.per_type.iter().enumerate() {
+                    crate::println!(
+                        "  {:<18} {}",
+                        crate::perf::trace::event_type_name(t as u8),
+                        count
+                    );
+                }
+            }
+            "clear" => {
+                crate::perf::trace::clear();
+                crate::println!("Trace buffers cleared.");
+            }
+            "events" if args.len() > 2 => {
+                let mut mask = 0u32;
+                for name in args[2].split(',') {
+                    if name == "all" {
+                        mask = crate::perf::trace::ALL_EVENTS;
+                        continue;
+                    }
+                    match crate::perf::trace::event_type_from_name(name) {
+                        Some(t) => mask |= 1 << t,
+                        None => {
+                            crate::println!("trace: unknown event '{}'", name);
+                            return CommandResult::Success(1);
+                        }
+                    }
+                }
+                crate::perf::trace::set_event_mask(mask);
+                crate::println!("Event mask set to {:#x}.", mask);
+            }
+            "pid" if args.len() > 2 => {
+                let pid = match args[2].as_str() {
+                    "all" => 0,
+                    pid => match pid.parse::<u64>() {
+                        Ok(pid) => pid,
+                        Err(_) => {
+                            crate::println!("trace: invalid pid '{}'", pid);
+                            return CommandResult::Success(1);
+                        }
+                    },
+                };
+                crate::perf::trace::set_pid_filter(pid);
+                crate::println!("PID filter set to {}.", args[2]);
             }
             _ => {
                 crate::println!("Unknown trace command: {}", args[1]);
@@ -1375,6 +1564,63 @@ impl BuiltinCommand for SlabCommand {
     }
 }
 
+pub(in crate::services::shell) struct MemprofCommand;
+impl BuiltinCommand for MemprofCommand {
+    fn name(&self) -> &str {
+        "memprof"
+    }
+    fn description(&self) -> &str {
+        "Heap allocation profiler"
+    }
+    fn execute(&self, args: &[String], _shell: &Shell) -> CommandResult {
+        use crate::mm::heap_profile::{self, Mode};
+
+        let sub = args.first().map(|s| s.as_str()).unwrap_or("report");
+        match sub {
+            "report" => crate::print!("{}", heap_profile::report(20)),
+            "on" | "counts" => {
+                heap_profile::set_mode(Mode::Counts, heap_profile::sample_interval());
+                crate::println!("memprof: counting kernel allocations");
+            }
+            "sites" => {
+                let interval = match args.get(1).map(|n| n.parse::<u64>()) {
+                    None => heap_profile::DEFAULT_SAMPLE_INTERVAL,
+                    Some(Ok(n)) if n > 0 => n,
+                    Some(_) => {
+                        crate::println!("memprof: sites takes a positive sampling interval");
+                        return CommandResult::Error(String::from("bad interval"));
+                    }
+                };
+                heap_profile::set_mode(Mode::Sites, interval);
+                crate::println!(
+                    "memprof: counting kernel allocations, call sites of 1 in {}",
+                    interval
+                );
+            }
+            "off" => {
+                heap_profile::set_mode(Mode::Off, heap_profile::sample_interval());
+                crate::println!("memprof: off");
+            }
+            "reset" => {
+                heap_profile::reset();
+                crate::println!("memprof: counters reset");
+            }
+            pid => {
+                let Some(process) = pid
+                    .parse::<u64>()
+                    .ok()
+                    .and_then(|pid| crate::process::get_process(crate::process::ProcessId(pid)))
+                else {
+                    crate::println!("Usage: memprof [report|on|sites [interval]|off|reset|<pid>]");
+                    return CommandResult::Error(String::from("bad argument"));
+                };
+                crate::print!("{}", heap_profile::process_report(process));
+            }
+        }
+        CommandResult::Success(0)
+    }
+}
+
 // ============================================================================
 // NUMA & KPTI Diagnostics
 // ============================================================================
@@ -1845,13 +2091,7 @@ impl BuiltinCommand for ShutdownCommand {
         } else {
             crate::println!("shutdown: init system not available");
         }
-        // Try ACPI S5 via QEMU debug-exit on x86_64
-        #[cfg(target_arch = "x86_64")]
-        // SAFETY: Writing to QEMU debug-exit port 0xF4 to trigger VM shutdown.
-        unsafe {
-            x86_64::instructions::port::Port::new(0xf4).write(0x00u32);
-        }
-        CommandResult::Success(0)
+        crate::power::system::power_off()
     }
 }
 
@@ -1872,7 +2112,7 @@ impl BuiltinCommand for RebootCommand {
         } else {
             crate::println!("reboot: init system not available");
         }
-        CommandResult::Success(0)
+        crate::power::system::reboot()
     }
 }
 
@@ -1893,12 +2133,7 @@ impl BuiltinCommand for PoweroffCommand {
         } else {
             crate::println!("poweroff: init system not available");
         }
-        #[cfg(target_arch = "x86_64")]
-        // SAFETY: Writing to QEMU debug-exit port 0xF4 to trigger VM power-off.
-        unsafe {
-            x86_64::instructions::port::Port::new(0xf4).write(0x00u32);
-        }
-        CommandResult::Success(0)
+        crate::power::system::power_off()
     }
 }
 
@@ -1912,9 +2147,16 @@ impl BuiltinCommand for SuspendCommand {
     }
     fn execute(&self, _args: &[String], _shell: &Shell) -> CommandResult {
         crate::println!("Entering suspend (S3)...");
-        crate::power::enter_idle(crate::power::CState::C3);
-        crate::println!("Resumed from suspend");
-        CommandResult::Success(0)
+        match crate::power::system::suspend() {
+            Ok(()) => {
+                crate::println!("Resumed from suspend");
+                CommandResult::Success(0)
+            }
+            Err(e) => {
+                crate::println!("suspend: {}", e);
+                CommandResult::Success(1)
+            }
+        }
     }
 }
 
@@ -2317,7 +2559,8 @@ impl BuiltinCommand for TopCommand {
         );
 
         let ps = crate::services::process_server::get_process_server();
-        let processes = ps.list_processes();
+        let mut processes = ps.list_processes();
+        processes.sort_by_key(|p| core::cmp::Reverse(p.cpu_permille()));
         for p in &processes {
             let name_display: String = if p.name.len() > 15 {
                 p.name[..15].into()
@@ -2329,8 +2572,8 @@ impl BuiltinCommand for TopCommand {
                 p.pid.0,
                 name_display,
                 format!("{:?}", p.state),
-                "0%",
-                "0K"
+                format_cpu_percent(p.cpu_permille()),
+                format!("{}K", p.memory_usage / 1024)
             );
         }
 
@@ -2564,10 +2807,59 @@ impl BuiltinCommand for SysctlCommand {
             crate::println!("kernel.uptime = {}s", uptime);
             crate::println!("kernel.hostname = veridian");
             crate::println!("kernel.ostype = VeridianOS");
+            for limit in crate::limits::Limit::ALL {
+                crate::println!(
+                    "kernel.limits.{} = {}",
+                    limit.name(),
+                    crate::limits::get(limit)
+                );
+            }
+            for param in crate::utils::cmdline::params() {
+                crate::println!("kernel.param.{} = {}", param.name(), param.show());
+            }
         } else if let Some(eq_pos) = arg.find('=') {
             let key = &arg[..eq_pos];
             let value = &arg[eq_pos + 1..];
-            crate::println!("{} = {}", key, value);
+            let limit = key.strip_prefix("kernel.limits.");
+            let param = key.strip_prefix("kernel.param.");
+            if limit.is_some() || param.is_some() {
+                let privileged = crate::process::current_process()
+                    .is_none_or(|p| p.credentials().is_privileged());
+                if !privileged {
+                    return CommandResult::Error(format!(
+                        "sysctl: permission denied on key '{}'",
+                        key
+                    ));
+                }
+            }
+            if let Some(name) = limit {
+                match crate::limits::set_by_name(name, value) {
+                    Ok(applied) => crate::println!("{} = {}", key, applied),
+                    Err(e) => {
+                        return CommandResult::Error(format!("sysctl: {}: {:?}", key, e));
+                    }
+                }
+            } else if let Some(name) = param {
+                match crate::utils::cmdline::tune(name, value) {
+                    Ok(applied) => crate::println!("{} = {}", key, applied),
+                    Err(e) => {
+                        return CommandResult::Error(format!("sysctl: {}: {:?}", key, e));
+                    }
+                }
+            } else {
+                crate::println!("{} = {}", key, value);
+            }
+        } else if let Some(limit) = arg
+            .strip_prefix("kernel.limits.")
+            .and_then(crate::limits::Limit::from_name)
+        {
+            crate::println!("{} = {}", arg, crate::limits::get(limit));
+        } else if let Some(param) = arg.strip_prefix("kernel.param.").and_then(|name| {
+            crate::utils::cmdline::params()
+                .into_iter()
+                .find(|p| p.name() == name)
+        }) {
+            crate::println!("{} = {}", arg, param.show());
         } else {
             crate::println!("{} = (unknown)", arg);
         }
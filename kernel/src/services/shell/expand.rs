@@ -3,8 +3,13 @@
 //! Handles `$VAR`, `${VAR}`, `${VAR:-default}`, `${VAR:+alternate}`,
 //! `${#VAR}` (string length), `${VAR%pattern}` / `${VAR%%pattern}` (suffix
 //! removal), `${VAR#pattern}` / `${VAR##pattern}` (prefix removal), special
-//! variables (`$?`, `$$`, `$0`), tilde expansion, quote handling,
-//! backslash-dollar escaping, and command substitution (`$(command)`).
+//! variables (`$?`, `$$`, `$0`), arithmetic (`$((expr))`), tilde expansion,
+//! quote handling, backslash escaping, and command substitution
+//! (`$(command)`).
+//!
+//! The expansion itself is the `shell_syntax` expander shared with vsh;
+//! this module supplies the kernel's inline command substitution and
+//! applies it to a whole command line.
 
 #![allow(dead_code)]
 
@@ -17,6 +22,14 @@ use alloc::{
     vec::Vec,
 };
 
+use shell_syntax::{
+    expand::{
+        parameter::{self, Context, SpecialVars},
+        tilde, Executor,
+    },
+    parser::word,
+};
+
 /// Expand variables, tildes, and quotes in the input string.
 ///
 /// - `$VAR` and `${VAR}` are looked up in `env`.
@@ -39,443 +52,86 @@ pub fn expand_variables(
     env: &BTreeMap<String, String>,
     last_exit_code: i32,
 ) -> String {
-    let mut result = String::with_capacity(input.len());
-    let chars: Vec<char> = input.chars().collect();
-    let len = chars.len();
-    let mut i = 0;
-
-    // Track quoting state
-    let mut in_single_quote = false;
-    let mut in_double_quote = false;
-
-    while i < len {
-        let ch = chars[i];
-
-        // ---- Single-quote toggle (not inside double quotes) ----
-        if ch == '\'' && !in_double_quote {
-            in_single_quote = !in_single_quote;
-            i += 1;
-            continue;
-        }
-
-        // Inside single quotes: literal pass-through
-        if in_single_quote {
-            result.push(ch);
-            i += 1;
-            continue;
-        }
-
-        // ---- Double-quote toggle ----
-        if ch == '"' && !in_single_quote {
-            in_double_quote = !in_double_quote;
-            i += 1;
-            continue;
-        }
-
-        // ---- Backslash-dollar escape ----
-        if ch == '\\' && i + 1 < len && chars[i + 1] == '$' {
-            result.push('$');
-            i += 2;
-            continue;
-        }
-
-        // ---- Tilde expansion (only at word start, outside quotes) ----
-        if ch == '~' && !in_double_quote && (i == 0 || chars[i - 1].is_whitespace()) {
-            // Check that tilde is at word boundary (next is / , whitespace, or end)
-            let next_is_sep = i + 1 >= len || chars[i + 1] == '/' || chars[i + 1].is_whitespace();
-            if next_is_sep {
-                if let Some(home) = env.get("HOME") {
-                    result.push_str(home);
-                } else {
-                    result.push('~');
-                }
-                i += 1;
-                continue;
-            }
-        }
+    let special = SpecialVars {
+        exit_status: last_exit_code,
+        pid: 1,
+        ..SpecialVars::default()
+    };
+    let mut exec = InlineExecutor {
+        env,
+        last_exit_code,
+    };
+    let tilded = expand_tildes(input, env);
+    let expanded = parameter::expand_parameters(&tilded, env, &special, &mut exec, Context::Word);
+    word::remove_quotes(&expanded)
+}
 
-        // ---- Command substitution: $(...) ----
-        if ch == '$' && i + 1 < len && chars[i + 1] == '(' {
-            i += 2; // skip "$("
-            let (expanded, consumed) =
-                expand_command_substitution(&chars[i..], env, last_exit_code);
-            result.push_str(&expanded);
-            i += consumed;
+/// Apply tilde expansion to every unquoted word of `input` that starts
+/// with `~`.
+fn expand_tildes(input: &str, env: &BTreeMap<String, String>) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut quote: Option<char> = None;
+    let mut word_start = true;
+    let mut rest = input;
+
+    while let Some(ch) = rest.chars().next() {
+        if word_start && quote.is_none() && ch == '~' {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            result.push_str(&tilde::expand_tilde(&rest[..end], env));
+            rest = &rest[end..];
+            word_start = false;
             continue;
         }
-
-        // ---- Variable expansion ----
-        if ch == '$' && i + 1 < len {
-            let next = chars[i + 1];
-
-            // ${...} — braced expansion
-            if next == '{' {
-                i += 2; // skip "${"
-                let (expanded, consumed) = expand_braced(&chars[i..], env);
-                result.push_str(&expanded);
-                i += consumed;
-                continue;
-            }
-
-            // $? — last exit code
-            if next == '?' {
-                result.push_str(&format!("{}", last_exit_code));
-                i += 2;
-                continue;
-            }
-
-            // $$ — kernel PID (always 1)
-            if next == '$' {
-                result.push('1');
-                i += 2;
-                continue;
-            }
-
-            // $0 — shell name
-            if next == '0' {
-                result.push_str("vsh");
-                i += 2;
-                continue;
-            }
-
-            // $VAR — unbraced variable name
-            if is_var_start(next) {
-                i += 1; // skip '$'
-                let start = i;
-                while i < len && is_var_char(chars[i]) {
-                    i += 1;
-                }
-                let var_name: String = chars[start..i].iter().collect();
-                if let Some(val) = env.get(&var_name) {
-                    result.push_str(val);
-                }
-                // If unset, expand to empty string (Bash behavior)
-                continue;
-            }
-
-            // Bare '$' at end of string or followed by non-var char — literal
-            result.push('$');
-            i += 1;
-            continue;
+        match quote {
+            Some(q) if ch == q => quote = None,
+            None if ch == '\'' || ch == '"' => quote = Some(ch),
+            _ => {}
         }
-
-        // ---- Default: pass character through ----
+        word_start = quote.is_none() && ch.is_whitespace();
         result.push(ch);
-        i += 1;
+        rest = &rest[ch.len_utf8()..];
     }
 
     result
 }
 
-// ---------------------------------------------------------------------------
-// Braced expansion helpers
-// ---------------------------------------------------------------------------
-
-/// Parse a `${...}` expression starting right after the `{`.
-///
-/// Returns `(expanded_value, chars_consumed)` where `chars_consumed` includes
-/// the closing `}`.
-fn expand_braced(chars: &[char], env: &BTreeMap<String, String>) -> (String, usize) {
-    let len = chars.len();
-
-    // Find closing brace
-    let close = match find_closing_brace(chars) {
-        Some(pos) => pos,
-        None => {
-            // No closing brace — return literal "${" + rest
-            let literal: String = chars.iter().collect();
-            return (format!("${{{}", literal), len);
-        }
-    };
-
-    let inner: String = chars[..close].iter().collect();
-    let consumed = close + 1; // +1 for '}'
-
-    // ${#VAR} — string length
-    if let Some(var_name) = inner.strip_prefix('#') {
-        let length = env.get(var_name).map(|v| v.len()).unwrap_or(0);
-        return (format!("{}", length), consumed);
-    }
-
-    // ${VAR%%pattern} — longest suffix removal (check before single %)
-    if let Some(pos) = inner.find("%%") {
-        let var_name = &inner[..pos];
-        let pattern = &inner[pos + 2..];
-        let value = env.get(var_name).cloned().unwrap_or_default();
-        let trimmed = remove_suffix_longest(&value, pattern);
-        return (trimmed, consumed);
-    }
-
-    // ${VAR%pattern} — shortest suffix removal
-    if let Some(pos) = inner.find('%') {
-        let var_name = &inner[..pos];
-        let pattern = &inner[pos + 1..];
-        let value = env.get(var_name).cloned().unwrap_or_default();
-        let trimmed = remove_suffix_shortest(&value, pattern);
-        return (trimmed, consumed);
-    }
-
-    // ${VAR##pattern} — longest prefix removal (check before single #)
-    if let Some(pos) = inner.find("##") {
-        let var_name = &inner[..pos];
-        let pattern = &inner[pos + 2..];
-        let value = env.get(var_name).cloned().unwrap_or_default();
-        let trimmed = remove_prefix_longest(&value, pattern);
-        return (trimmed, consumed);
-    }
-
-    // ${VAR#pattern} — shortest prefix removal
-    // Must not misinterpret ${#VAR} which is handled above.
-    if let Some(pos) = inner.find('#') {
-        // Only treat as prefix removal if '#' is not at position 0
-        // (position 0 is the ${#VAR} length syntax, already handled).
-        if pos > 0 {
-            let var_name = &inner[..pos];
-            let pattern = &inner[pos + 1..];
-            let value = env.get(var_name).cloned().unwrap_or_default();
-            let trimmed = remove_prefix_shortest(&value, pattern);
-            return (trimmed, consumed);
-        }
-    }
-
-    // ${VAR:-default} — default value if unset or empty
-    if let Some(pos) = inner.find(":-") {
-        let var_name = &inner[..pos];
-        let default_val = &inner[pos + 2..];
-        let value = env.get(var_name).cloned().unwrap_or_default();
-        if value.is_empty() {
-            return (default_val.to_string(), consumed);
-        }
-        return (value, consumed);
-    }
-
-    // ${VAR:+alternate} — alternate value if set and non-empty
-    if let Some(pos) = inner.find(":+") {
-        let var_name = &inner[..pos];
-        let alternate = &inner[pos + 2..];
-        let value = env.get(var_name).cloned().unwrap_or_default();
-        if !value.is_empty() {
-            return (alternate.to_string(), consumed);
-        }
-        return (String::new(), consumed);
-    }
-
-    // Plain ${VAR}
-    let value = env.get(inner.as_str()).cloned().unwrap_or_default();
-    (value, consumed)
-}
-
-/// Find the index of the closing `}` that matches the opening `{`.
-/// The input slice starts right after the opening `{`.
-fn find_closing_brace(chars: &[char]) -> Option<usize> {
-    let mut depth = 1u32;
-    for (i, &ch) in chars.iter().enumerate() {
-        match ch {
-            '{' => depth += 1,
-            '}' => {
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
-                }
-            }
-            _ => {}
-        }
-    }
-    None
-}
-
-/// Returns true if `ch` can be the first character of a variable name.
-fn is_var_start(ch: char) -> bool {
-    ch.is_ascii_alphabetic() || ch == '_'
-}
-
-/// Returns true if `ch` can appear in a variable name (after the first char).
-fn is_var_char(ch: char) -> bool {
-    ch.is_ascii_alphanumeric() || ch == '_'
+/// Runs command substitutions with the inline evaluators below; the kernel
+/// shell cannot capture a process's standard output.
+struct InlineExecutor<'a> {
+    env: &'a BTreeMap<String, String>,
+    last_exit_code: i32,
 }
 
-// ---------------------------------------------------------------------------
-// Pattern matching helpers for suffix/prefix removal
-// ---------------------------------------------------------------------------
-
-/// Glob-like pattern match for parameter expansion.
-///
-/// Unlike filename glob where `*` does not match `/`, parameter expansion
-/// patterns allow `*` and `?` to match ANY character (POSIX spec).
-fn pattern_match(pattern: &str, text: &str) -> bool {
-    let pat = pattern.as_bytes();
-    let txt = text.as_bytes();
-    let (plen, tlen) = (pat.len(), txt.len());
-    let (mut pi, mut ti) = (0usize, 0usize);
-    let mut star_pi: Option<usize> = None;
-    let mut star_ti = 0usize;
-
-    while ti < tlen {
-        if pi < plen && (pat[pi] == b'?' || pat[pi] == txt[ti]) {
-            pi += 1;
-            ti += 1;
-        } else if pi < plen && pat[pi] == b'*' {
-            star_pi = Some(pi);
-            star_ti = ti;
-            pi += 1;
-        } else if let Some(sp) = star_pi {
-            pi = sp + 1;
-            star_ti += 1;
-            ti = star_ti;
-        } else {
-            return false;
-        }
+impl Executor for InlineExecutor<'_> {
+    fn command(&mut self, cmd: &str) -> String {
+        // Nested substitutions and variables in the command come first
+        let expanded = expand_variables(cmd, self.env, self.last_exit_code);
+        execute_substitution_command(expanded.trim())
     }
 
-    while pi < plen && pat[pi] == b'*' {
-        pi += 1;
+    fn process(&mut self, _cmd: &str, _output: bool) -> String {
+        // No pipes to name: process substitution expands to nothing
+        String::new()
     }
-    pi == plen
-}
-
-/// Remove the shortest trailing substring that matches `pattern`.
-fn remove_suffix_shortest(value: &str, pattern: &str) -> String {
-    if pattern.is_empty() {
-        return value.to_string();
-    }
-    // Try suffixes from shortest to longest (scan from end toward start)
-    for (i, _) in value.char_indices().rev() {
-        if pattern_match(pattern, &value[i..]) {
-            return value[..i].to_string();
-        }
-    }
-    // Try the entire string as suffix
-    if pattern_match(pattern, value) {
-        return String::new();
-    }
-    value.to_string()
-}
 
-/// Remove the longest trailing substring that matches `pattern`.
-fn remove_suffix_longest(value: &str, pattern: &str) -> String {
-    if pattern.is_empty() {
-        return value.to_string();
+    fn assign(&mut self, _name: &str, _value: &str) {
+        // The environment is a snapshot here; `$(( ))` assignments are
+        // dropped
     }
-    // Try the entire string first (longest), then shorter
-    if pattern_match(pattern, value) {
-        return String::new();
-    }
-    for (i, _) in value.char_indices() {
-        if pattern_match(pattern, &value[i..]) {
-            return value[..i].to_string();
-        }
-    }
-    value.to_string()
-}
 
-/// Remove the shortest leading substring that matches `pattern`.
-fn remove_prefix_shortest(value: &str, pattern: &str) -> String {
-    if pattern.is_empty() {
-        return value.to_string();
-    }
-    // Try prefixes from shortest to longest (skip empty prefix at 0)
-    for i in value
-        .char_indices()
-        .map(|(i, _)| i)
-        .chain(core::iter::once(value.len()))
-        .skip(1)
-    {
-        if pattern_match(pattern, &value[..i]) {
-            return value[i..].to_string();
-        }
+    fn read_dir(&mut self, path: &str) -> Option<Vec<String>> {
+        super::glob::list_directory(path)
     }
-    value.to_string()
-}
 
-/// Remove the longest leading substring that matches `pattern`.
-fn remove_prefix_longest(value: &str, pattern: &str) -> String {
-    if pattern.is_empty() {
-        return value.to_string();
-    }
-    // Try prefixes from longest to shortest
-    if pattern_match(pattern, value) {
-        return String::new();
-    }
-    for (i, _) in value.char_indices().rev() {
-        let end = i + value[i..].chars().next().map_or(0, |c| c.len_utf8());
-        if pattern_match(pattern, &value[..end]) {
-            return value[end..].to_string();
-        }
+    fn error(&mut self, msg: &str) {
+        crate::println!("vsh: {}", msg);
     }
-    value.to_string()
 }
 
 // ---------------------------------------------------------------------------
 // Command substitution helpers
 // ---------------------------------------------------------------------------
 
-/// Expand a `$(...)` command substitution starting right after the `(`.
-///
-/// Returns `(expanded_output, chars_consumed)` where `chars_consumed` includes
-/// the closing `)`.
-///
-/// In kernel space without full stdout capture, we support a limited set of
-/// inline commands:
-/// - `$(echo ...)`: returns the echo arguments (with variable expansion)
-/// - `$(cat /path)`: reads the file contents from VFS
-/// - Other commands: return empty string (full stdout capture requires process
-///   infrastructure that is not yet available)
-///
-/// Nested `$(...)` are handled recursively.
-fn expand_command_substitution(
-    chars: &[char],
-    env: &BTreeMap<String, String>,
-    last_exit_code: i32,
-) -> (String, usize) {
-    let len = chars.len();
-
-    // Find closing paren, respecting nesting
-    let close = match find_closing_paren(chars) {
-        Some(pos) => pos,
-        None => {
-            // No closing paren — return literal "$(" + rest
-            let literal: String = chars.iter().collect();
-            return (format!("$({}", literal), len);
-        }
-    };
-
-    let inner: String = chars[..close].iter().collect();
-    let consumed = close + 1; // +1 for ')'
-
-    // Recursively expand any nested $() in the inner command first
-    let expanded_inner = expand_variables(&inner, env, last_exit_code);
-
-    // Parse the command
-    let trimmed = expanded_inner.trim();
-    let output = execute_substitution_command(trimmed);
-
-    (output, consumed)
-}
-
-/// Find the index of the closing `)` that matches the opening `(`.
-/// The input slice starts right after the opening `(`.
-fn find_closing_paren(chars: &[char]) -> Option<usize> {
-    let mut depth = 1u32;
-    let mut i = 0;
-    while i < chars.len() {
-        match chars[i] {
-            '(' => {
-                // Check for nested $( by looking at preceding char
-                depth += 1;
-            }
-            ')' => {
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
-                }
-            }
-            _ => {}
-        }
-        i += 1;
-    }
-    None
-}
-
 /// Execute a command for substitution and return its captured output.
 ///
 /// Supports inline evaluation for common shell builtins and utilities:
@@ -1288,6 +944,13 @@ mod tests {
         assert_eq!(expand_variables("~", &env, 0), "~");
     }
 
+    #[test]
+    fn test_tilde_later_word() {
+        let env = env_with(&[("HOME", "/root")]);
+        assert_eq!(expand_variables("ls ~/docs", &env, 0), "ls /root/docs");
+        assert_eq!(expand_variables("echo \"~/docs\"", &env, 0), "echo ~/docs");
+    }
+
     // ---- Quoting ----
 
     #[test]
@@ -1336,6 +999,14 @@ mod tests {
         assert_eq!(expand_variables("cost is $", &env, 0), "cost is $");
     }
 
+    // ---- Arithmetic ----
+
+    #[test]
+    fn test_arithmetic() {
+        let env = env_with(&[("N", "4")]);
+        assert_eq!(expand_variables("$((N * 2 + 1))", &env, 0), "9");
+    }
+
     // ---- Command substitution ----
 
     #[test]
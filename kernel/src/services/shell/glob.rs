@@ -1,8 +1,8 @@
 //! Glob pattern matching and expansion for the VeridianOS shell.
 //!
-//! Provides `glob_match` for testing whether a pattern matches a given
-//! string, and `expand_globs` for expanding glob tokens against the VFS
-//! directory tree.
+//! Provides `expand_globs` for expanding glob tokens against the VFS
+//! directory tree.  Pattern matching itself is the shared `shell_syntax`
+//! matcher used by vsh, re-exported here as `glob_match`.
 //!
 //! Supported patterns:
 //! - `*` matches any sequence of characters (except `/`)
@@ -18,6 +18,8 @@ use alloc::{
     vec::Vec,
 };
 
+pub use shell_syntax::expand::glob::{contains_glob_chars, glob_match};
+
 /// Expand glob tokens against the VFS.
 ///
 /// For each token that contains `*`, `?`, or `[`, attempt to list matching
@@ -76,166 +78,10 @@ pub fn expand_globs(tokens: Vec<String>, cwd: &str) -> Vec<String> {
     result
 }
 
-/// Test whether `pattern` matches `text`.
-///
-/// The pattern language supports:
-/// - `*` — matches zero or more characters (not `/`)
-/// - `?` — matches exactly one character (not `/`)
-/// - `[abc]` — matches any one of the listed characters
-/// - `[a-z]` — matches a character in the range
-/// - `[!...]` or `[^...]` — negated character class
-/// - Any other character matches itself literally.
-pub fn glob_match(pattern: &str, text: &str) -> bool {
-    let pat: Vec<char> = pattern.chars().collect();
-    let txt: Vec<char> = text.chars().collect();
-    glob_match_recursive(&pat, 0, &txt, 0)
-}
-
 // ---------------------------------------------------------------------------
 // Internal helpers
 // ---------------------------------------------------------------------------
 
-/// Recursive glob matcher with backtracking for `*`.
-fn glob_match_recursive(pat: &[char], pi: usize, txt: &[char], ti: usize) -> bool {
-    let plen = pat.len();
-    let tlen = txt.len();
-    let mut pi = pi;
-    let mut ti = ti;
-
-    // Track the last `*` position for backtracking
-    let mut star_pi: Option<usize> = None;
-    let mut star_ti: usize = 0;
-
-    while ti < tlen {
-        if pi < plen && pat[pi] == '?' && txt[ti] != '/' {
-            // '?' matches any single non-slash character
-            pi += 1;
-            ti += 1;
-        } else if pi < plen && pat[pi] == '*' {
-            // '*' matches zero or more non-slash characters
-            // Record position for backtracking
-            star_pi = Some(pi);
-            star_ti = ti;
-            pi += 1;
-            // Try matching zero characters first (advance pattern only)
-        } else if pi < plen && pat[pi] == '[' {
-            // Character class
-            let (matched, end) = match_char_class(&pat[pi..], txt[ti]);
-            if matched {
-                pi += end;
-                ti += 1;
-            } else if let Some(sp) = star_pi {
-                // Backtrack: let '*' consume one more character, but not '/'
-                if txt[star_ti] == '/' {
-                    return false;
-                }
-                pi = sp + 1;
-                star_ti += 1;
-                ti = star_ti;
-            } else {
-                return false;
-            }
-        } else if pi < plen && pat[pi] == txt[ti] {
-            // Exact character match
-            pi += 1;
-            ti += 1;
-        } else if let Some(sp) = star_pi {
-            // Mismatch — backtrack to last '*', but '*' must not consume '/'
-            if txt[star_ti] == '/' {
-                return false;
-            }
-            pi = sp + 1;
-            star_ti += 1;
-            ti = star_ti;
-        } else {
-            return false;
-        }
-    }
-
-    // Consume any remaining '*' in pattern
-    while pi < plen && pat[pi] == '*' {
-        pi += 1;
-    }
-
-    pi == plen
-}
-
-/// Match a character class `[...]` starting at `chars[0]` which must be `[`.
-///
-/// Returns `(matched, consumed)` where `consumed` is the number of chars in
-/// the pattern consumed by the class (including the closing `]`).
-fn match_char_class(chars: &[char], ch: char) -> (bool, usize) {
-    let len = chars.len();
-    if len < 2 || chars[0] != '[' {
-        return (false, 0);
-    }
-
-    let mut i = 1;
-    let negated = if i < len && (chars[i] == '!' || chars[i] == '^') {
-        i += 1;
-        true
-    } else {
-        false
-    };
-
-    let mut matched = false;
-
-    // A leading ']' right after '[' or '[!' is treated as a literal ']'
-    if i < len && chars[i] == ']' {
-        if ch == ']' {
-            matched = true;
-        }
-        i += 1;
-    }
-
-    while i < len && chars[i] != ']' {
-        // Check for range: a-z
-        if i + 2 < len && chars[i + 1] == '-' && chars[i + 2] != ']' {
-            let lo = chars[i];
-            let hi = chars[i + 2];
-            if ch >= lo && ch <= hi {
-                matched = true;
-            }
-            i += 3;
-        } else {
-            if chars[i] == ch {
-                matched = true;
-            }
-            i += 1;
-        }
-    }
-
-    // Skip closing ']'
-    if i < len && chars[i] == ']' {
-        i += 1;
-    } else {
-        // No closing bracket — treat the whole thing as literal (no match)
-        return (false, 0);
-    }
-
-    let result = if negated { !matched } else { matched };
-    (result, i)
-}
-
-/// Return true if the string contains any unescaped glob metacharacters.
-fn contains_glob_chars(s: &str) -> bool {
-    let mut escaped = false;
-    for ch in s.chars() {
-        if escaped {
-            escaped = false;
-            continue;
-        }
-        if ch == '\\' {
-            escaped = true;
-            continue;
-        }
-        if ch == '*' || ch == '?' || ch == '[' {
-            return true;
-        }
-    }
-    false
-}
-
 /// Split a glob token into (directory, pattern).
 ///
 /// For example:
@@ -268,7 +114,7 @@ fn split_dir_pattern(token: &str, cwd: &str) -> (String, String) {
 /// List directory entries from the VFS.
 ///
 /// Returns `Some(names)` on success, `None` if the directory cannot be read.
-fn list_directory(path: &str) -> Option<Vec<String>> {
+pub(super) fn list_directory(path: &str) -> Option<Vec<String>> {
     let vfs = crate::fs::try_get_vfs()?;
     let vfs_guard = vfs.read();
     let node = vfs_guard.resolve_path(path).ok()?;
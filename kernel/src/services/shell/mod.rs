@@ -40,34 +40,43 @@ use alloc::{
 };
 
 use commands::{
-    AcpiCommand, AliasCommand, ArpCommand, AtCommand, AuditCommand, BgCommand, Blake3sumCommand,
-    BlkidCommand, BondCommand, BracketTestCommand, BrowserCommand, BtCommand, CapCommand,
-    CatCommand, CdCommand, ChmodCommand, CiCommand, ClearCommand, CloudInitCommand,
-    ContainerCommand, CoredumpCommand, CpCommand, CrontabCommand, CurlCommand, CutCommand,
-    DateCommand, DfCommand, DhcpCommand, DmesgCommand, DnsCommand, DotCommand, EchoCommand,
-    EnvCommand, ExitCommand, ExportCommand, FalseCommand, FgCommand, FirewallCommand, FreeCommand,
-    FsckCommand, GdbCommand, GitCommand, GrepCommand, GroupsCommand, HeadCommand, HelpCommand,
-    HibernateCommand, HistoryCommand, HostnameCommand, HttpServerCommand, HwinfoCommand, IdCommand,
-    IfconfigCommand, IpcsCommand, IscsiadmCommand, JobsCommand, KillCommand, KinitCommand,
-    KlistCommand, KptiCommand, KubectlCommand, LdapsearchCommand, LsCommand, LsblkCommand,
-    LscpuCommand, LsmodCommand, LsnsCommand, LspciCommand, LsusbCommand, MacCommand, MakeCommand,
-    MdadmCommand, MkdirCommand, MkfsCommand, MountCommand, MvCommand, NatCommand, NdpCommand,
-    NetstatCommand, NfsmountCommand, NotifyCommand, NtpCommand, NumaCommand, PasswdCommand,
-    PerfCommand, Ping6Command, PingCommand, PkgCommand, PlayCommand, PoweroffCommand,
-    PrintfCommand, ProfilerCommand, PsCommand, PwdCommand, ReadCommand, RebootCommand, RmCommand,
-    RouteCommand, SchedCommand, ScreenshotCommand, ServiceCommand, SetCommand, Sha256sumCommand,
-    ShutdownCommand, SlabCommand, SmbclientCommand, SortCommand, SourceCommand, SsCommand,
-    SshCommand, SshdCommand, StartGuiCommand, StraceCommand, SuCommand, SudoCommand,
-    SuspendCommand, SyncCommand, SysctlCommand, TailCommand, TarCommand, TeeCommand, TestCommand,
-    ThemeCommand, TopCommand, TouchCommand, TpmCommand, TrCommand, TraceCommand, TrueCommand,
-    TypeCommand, UnaliasCommand, UnameCommand, UniqCommand, UnsetCommand, UptimeCommand,
-    UseraddCommand, UserdelCommand, VlanCommand, VmstatCommand, VmxCommand, VolumeCommand,
-    VpnCommand, WcCommand, WgCommand, WhichCommand, WhoamiCommand, WifiCommand, WinfoCommand,
-    XattrCommand,
+    AcpiCommand, AliasCommand, AtCommand, AuditCommand, BenchCommand, BgCommand, Blake3sumCommand,
+    BlkidCommand, BracketTestCommand, CapCommand, CatCommand, CdCommand, ChmodCommand, CiCommand,
+    ClearCommand, CloudInitCommand, ContainerCommand, CoredumpCommand, CpCommand, CrontabCommand,
+    CutCommand, DateCommand, DfCommand, DmesgCommand, DotCommand, EchoCommand, EnvCommand,
+    ExitCommand, ExportCommand, FalseCommand, FgCommand, FreeCommand, FsckCommand, GdbCommand,
+    GitCommand, GrepCommand, GroupsCommand, HeadCommand, HelpCommand, HibernateCommand,
+    HistoryCommand, HostnameCommand, HwinfoCommand, IdCommand, InputRecCommand, IpcsCommand,
+    IscsiadmCommand, JobsCommand, KillCommand, KptiCommand, KubectlCommand, LosetupCommand,
+    LsCommand, LsblkCommand, LscpuCommand, LsmodCommand, LsnsCommand, LspciCommand, LsusbCommand,
+    MacCommand, MakeCommand, MdadmCommand, MemprofCommand, MkdirCommand, MkfsCommand, MountCommand,
+    MvCommand, NfsmountCommand, NumaCommand, PasswdCommand, PerfCommand, PlayCommand,
+    PoweroffCommand, PrintfCommand, ProfilerCommand, PsCommand, PwdCommand, ReadCommand,
+    RebootCommand, RmCommand, SandboxCommand, SchedCommand, ServiceCommand, SetCommand,
+    Sha256sumCommand, ShutdownCommand, SlabCommand, SmbclientCommand, SortCommand, SourceCommand,
+    StraceCommand, SuCommand, SudoCommand, SuspendCommand, SyncCommand, SysctlCommand, TailCommand,
+    TarCommand, TeeCommand, TestCommand, TopCommand, TouchCommand, TpmCommand, TrCommand,
+    TraceCommand, TrueCommand, TypeCommand, UnaliasCommand, UnameCommand, UniqCommand,
+    UnsetCommand, UptimeCommand, UseraddCommand, UserdelCommand, VmstatCommand, VmxCommand,
+    VolumeCommand, WasmCommand, WcCommand, WhichCommand, WhoamiCommand, XattrCommand,
 };
-use spin::RwLock;
+#[cfg(feature = "net")]
+use commands::{
+    ArpCommand, BondCommand, BtCommand, CurlCommand, DhcpCommand, DnsCommand, FirewallCommand,
+    HttpServerCommand, IfconfigCommand, KinitCommand, KlistCommand, LdapsearchCommand, NatCommand,
+    NdpCommand, NetstatCommand, NtpCommand, Ping6Command, PingCommand, RouteCommand, SsCommand,
+    SshCommand, SshdCommand, VlanCommand, VpnCommand, WgCommand, WifiCommand,
+};
+#[cfg(feature = "desktop")]
+use commands::{
+    BrowserCommand, NotifyCommand, ScreenshotCommand, StartGuiCommand, ThemeCommand, WinfoCommand,
+};
+#[cfg(feature = "pkg")]
+use commands::{PkgCommand, SyslogCommand};
 pub use state::{get_shell, init, run_shell, try_get_shell};
 
+use crate::sync::lockdep::RwLock;
+
 /// Command execution result
 #[derive(Debug)]
 pub enum CommandResult {
@@ -232,7 +241,10 @@ impl Shell {
                 }
                 CommandResult::NotFound => {
                     if !command_line.trim().is_empty() {
-                        crate::println!("vsh: command not found");
+                        crate::println!(
+                            "vsh: {}",
+                            crate::services::locale::tr("command not found")
+                        );
                         *self.last_exit_code.write() = 127;
                     }
                 }
@@ -690,7 +702,10 @@ impl Shell {
         builtins.insert("unset".into(), Box::new(UnsetCommand));
 
         // Package management
-        builtins.insert("pkg".into(), Box::new(PkgCommand));
+        #[cfg(feature = "pkg")]
+        {
+            builtins.insert("pkg".into(), Box::new(PkgCommand));
+        }
 
         // Shell commands
         builtins.insert("history".into(), Box::new(HistoryCommand));
@@ -748,21 +763,28 @@ impl Shell {
 
         // Performance commands
         builtins.insert("perf".into(), Box::new(PerfCommand));
+        builtins.insert("bench".into(), Box::new(BenchCommand));
         builtins.insert("trace".into(), Box::new(TraceCommand));
 
         // Hardware diagnostics commands
         builtins.insert("acpi".into(), Box::new(AcpiCommand));
 
         // Network commands
-        builtins.insert("ifconfig".into(), Box::new(IfconfigCommand));
-        builtins.insert("dhcp".into(), Box::new(DhcpCommand));
-        builtins.insert("netstat".into(), Box::new(NetstatCommand));
-        builtins.insert("arp".into(), Box::new(ArpCommand));
-        builtins.insert("ping6".into(), Box::new(Ping6Command));
-        builtins.insert("ndp".into(), Box::new(NdpCommand));
+        #[cfg(feature = "net")]
+        {
+            builtins.insert("ifconfig".into(), Box::new(IfconfigCommand));
+            builtins.insert("dhcp".into(), Box::new(DhcpCommand));
+            builtins.insert("netstat".into(), Box::new(NetstatCommand));
+            builtins.insert("arp".into(), Box::new(ArpCommand));
+            builtins.insert("ping6".into(), Box::new(Ping6Command));
+            builtins.insert("ndp".into(), Box::new(NdpCommand));
+        }
 
         // Desktop / GUI commands
-        builtins.insert("startgui".into(), Box::new(StartGuiCommand));
+        #[cfg(feature = "desktop")]
+        {
+            builtins.insert("startgui".into(), Box::new(StartGuiCommand));
+        }
 
         // Audio commands
         builtins.insert("play".into(), Box::new(PlayCommand));
@@ -784,12 +806,14 @@ impl Shell {
         // Memory & performance commands
         builtins.insert("sched".into(), Box::new(SchedCommand));
         builtins.insert("slab".into(), Box::new(SlabCommand));
+        builtins.insert("memprof".into(), Box::new(MemprofCommand));
         builtins.insert("vmstat".into(), Box::new(VmstatCommand));
 
         // Security commands
         builtins.insert("audit".into(), Box::new(AuditCommand));
         builtins.insert("cap".into(), Box::new(CapCommand));
         builtins.insert("mac".into(), Box::new(MacCommand));
+        builtins.insert("sandbox".into(), Box::new(SandboxCommand));
         builtins.insert("tpm".into(), Box::new(TpmCommand));
 
         // Crypto commands
@@ -800,29 +824,40 @@ impl Shell {
         builtins.insert("ipcs".into(), Box::new(IpcsCommand));
 
         // Networking commands
-        builtins.insert("route".into(), Box::new(RouteCommand));
-        builtins.insert("ss".into(), Box::new(SsCommand));
+        #[cfg(feature = "net")]
+        {
+            builtins.insert("route".into(), Box::new(RouteCommand));
+            builtins.insert("ss".into(), Box::new(SsCommand));
+        }
 
         // Extended network commands
-        builtins.insert("firewall".into(), Box::new(FirewallCommand));
-        builtins.insert("nat".into(), Box::new(NatCommand));
-        builtins.insert("dns".into(), Box::new(DnsCommand));
-        builtins.insert("ntp".into(), Box::new(NtpCommand));
-        builtins.insert("vpn".into(), Box::new(VpnCommand));
-        builtins.insert("wg".into(), Box::new(WgCommand));
-        builtins.insert("wifi".into(), Box::new(WifiCommand));
-        builtins.insert("bt".into(), Box::new(BtCommand));
-        builtins.insert("ssh".into(), Box::new(SshCommand));
-        builtins.insert("curl".into(), Box::new(CurlCommand));
-        builtins.insert("ping".into(), Box::new(PingCommand));
-        builtins.insert("vlan".into(), Box::new(VlanCommand));
-        builtins.insert("bond".into(), Box::new(BondCommand));
-        builtins.insert("ldapsearch".into(), Box::new(LdapsearchCommand));
-        builtins.insert("kinit".into(), Box::new(KinitCommand));
-        builtins.insert("klist".into(), Box::new(KlistCommand));
+        #[cfg(feature = "net")]
+        {
+            builtins.insert("firewall".into(), Box::new(FirewallCommand));
+            builtins.insert("nat".into(), Box::new(NatCommand));
+            builtins.insert("dns".into(), Box::new(DnsCommand));
+            builtins.insert("ntp".into(), Box::new(NtpCommand));
+            #[cfg(feature = "pkg")]
+            builtins.insert("syslog".into(), Box::new(SyslogCommand));
+            builtins.insert("vpn".into(), Box::new(VpnCommand));
+            builtins.insert("wg".into(), Box::new(WgCommand));
+            builtins.insert("wifi".into(), Box::new(WifiCommand));
+            builtins.insert("bt".into(), Box::new(BtCommand));
+            builtins.insert("ssh".into(), Box::new(SshCommand));
+            builtins.insert("curl".into(), Box::new(CurlCommand));
+            builtins.insert("ping".into(), Box::new(PingCommand));
+            builtins.insert("vlan".into(), Box::new(VlanCommand));
+            builtins.insert("bond".into(), Box::new(BondCommand));
+            builtins.insert("ldapsearch".into(), Box::new(LdapsearchCommand));
+            builtins.insert("kinit".into(), Box::new(KinitCommand));
+            builtins.insert("klist".into(), Box::new(KlistCommand));
+        }
 
         // Desktop commands
-        builtins.insert("winfo".into(), Box::new(WinfoCommand));
+        #[cfg(feature = "desktop")]
+        {
+            builtins.insert("winfo".into(), Box::new(WinfoCommand));
+        }
 
         // Virtualization / namespace commands
         builtins.insert("lsns".into(), Box::new(LsnsCommand));
@@ -833,6 +868,7 @@ impl Shell {
         builtins.insert("gdb".into(), Box::new(GdbCommand));
         builtins.insert("profiler".into(), Box::new(ProfilerCommand));
         builtins.insert("ci".into(), Box::new(CiCommand));
+        builtins.insert("wasm".into(), Box::new(WasmCommand));
 
         // System diagnostics
         builtins.insert("top".into(), Box::new(TopCommand));
@@ -850,14 +886,21 @@ impl Shell {
         builtins.insert("kubectl".into(), Box::new(KubectlCommand));
 
         // Server commands
-        builtins.insert("http-server".into(), Box::new(HttpServerCommand));
-        builtins.insert("sshd".into(), Box::new(SshdCommand));
+        #[cfg(feature = "net")]
+        {
+            builtins.insert("http-server".into(), Box::new(HttpServerCommand));
+            builtins.insert("sshd".into(), Box::new(SshdCommand));
+        }
 
         // Desktop commands (extended)
-        builtins.insert("screenshot".into(), Box::new(ScreenshotCommand));
-        builtins.insert("notify".into(), Box::new(NotifyCommand));
-        builtins.insert("theme".into(), Box::new(ThemeCommand));
-        builtins.insert("browser".into(), Box::new(BrowserCommand));
+        #[cfg(feature = "desktop")]
+        {
+            builtins.insert("screenshot".into(), Box::new(ScreenshotCommand));
+            builtins.insert("notify".into(), Box::new(NotifyCommand));
+            builtins.insert("theme".into(), Box::new(ThemeCommand));
+            builtins.insert("browser".into(), Box::new(BrowserCommand));
+        }
+        builtins.insert("inputrec".into(), Box::new(InputRecCommand));
 
         // User/group management
         builtins.insert("whoami".into(), Box::new(WhoamiCommand));
@@ -885,6 +928,7 @@ impl Shell {
         builtins.insert("tar".into(), Box::new(TarCommand));
         builtins.insert("xattr".into(), Box::new(XattrCommand));
         builtins.insert("mkfs".into(), Box::new(MkfsCommand));
+        builtins.insert("losetup".into(), Box::new(LosetupCommand));
         builtins.insert("fsck".into(), Box::new(FsckCommand));
         builtins.insert("blkid".into(), Box::new(BlkidCommand));
         builtins.insert("nfsmount".into(), Box::new(NfsmountCommand));
@@ -895,35 +939,13 @@ impl Shell {
         builtins.insert("iscsiadm".into(), Box::new(IscsiadmCommand));
     }
 
+    /// Split an expanded command line into words on whitespace; quoted
+    /// and backslash-escaped whitespace stays within its word.
     fn tokenize(&self, command_line: &str) -> Vec<String> {
-        let mut tokens = Vec::new();
-        let mut current_token = String::new();
-        let mut in_quotes = false;
-        let mut escape_next = false;
-
-        for ch in command_line.chars() {
-            if escape_next {
-                current_token.push(ch);
-                escape_next = false;
-            } else if ch == '\\' {
-                escape_next = true;
-            } else if ch == '"' {
-                in_quotes = !in_quotes;
-            } else if ch.is_whitespace() && !in_quotes {
-                if !current_token.is_empty() {
-                    tokens.push(current_token.clone());
-                    current_token.clear();
-                }
-            } else {
-                current_token.push(ch);
-            }
-        }
-
-        if !current_token.is_empty() {
-            tokens.push(current_token);
-        }
-
-        tokens
+        shell_syntax::parser::word::word_split(command_line, " \t\n")
+            .iter()
+            .map(|w| shell_syntax::parser::word::remove_quotes(w))
+            .collect()
     }
 
     fn execute_external_command(&self, command: &str, args: &[String]) -> CommandResult {
@@ -1170,16 +1192,12 @@ impl Shell {
                     }
                 }
                 None => {
-                    // No input available — yield CPU briefly.
-                    // We use spin_loop() rather than hlt/wfi because the APIC
-                    // is initialized and takes over interrupt routing from the
-                    // PIC, so PIC-based timer/keyboard IRQs may not fire.
-                    // Input is polled from serial + keyboard ring buffer.
-                    // Multiple iterations (~1us delay) reduces idle CPU usage
-                    // and gives QEMU's display thread more time to render.
-                    for _ in 0..256 {
-                        core::hint::spin_loop();
-                    }
+                    // No input available — idle until the next interrupt or
+                    // cpuidle wake-up. PIC-based keyboard IRQs may not fire
+                    // once the APIC takes over interrupt routing, so input
+                    // is polled from serial + keyboard ring buffer after
+                    // each wake-up rather than waited for.
+                    crate::power::cpuidle::idle();
                 }
             }
         }
@@ -0,0 +1,388 @@
+//! Service unit files
+//!
+//! Services are defined by one TOML file each in [`UNIT_DIR`], named after
+//! the service (`/etc/init/pkgd.toml` defines `pkgd`). The same files drive
+//! the user-space init (`userland/init`), which supervises the real
+//! processes; the kernel's [`InitSystem`](super::InitSystem) loads them so
+//! its `service` builtin sees the same services and start order.
+//!
+//! ```toml
+//! [unit]
+//! description = "Package service"
+//! requires = ["network"]   # started first; failure stops this unit
+//! after = ["logger"]       # ordering only
+//! critical = "reboot"      # once failed for good: "no" (default), "reboot"
+//!                          # or "rescue" (stop everything, console shell)
+//!
+//! [service]
+//! exec = "/bin/pkgd"
+//! args = ["-v"]
+//! env = ["PKGD_DEBUG=1"]
+//! type = "notify"          # "simple" (default) or "notify"
+//! restart = "on-failure"   # "no" (default), "on-failure" or "always"
+//! restart_delay_ms = 500   # first restart delay, doubled on each retry
+//! max_restarts = 5         # 0 (default): retry forever
+//!
+//! [health]
+//! check = "ping"           # "none" (default), "heartbeat" or "ping"
+//! interval_ms = 5000       # heartbeat period, or time between pings
+//! timeout_ms = 2000        # grace for a late heartbeat or ping reply
+//!
+//! [limits]                 # soft and hard RLIMIT_*; unset = unlimited
+//! memory_mb = 256          # address space (RLIMIT_AS)
+//! cpu_s = 3600             # CPU time (RLIMIT_CPU)
+//! open_files = 128         # descriptors (RLIMIT_NOFILE)
+//! processes = 32           # processes of the service's user (RLIMIT_NPROC)
+//!
+//! [filter]                 # syscall filter from exec on (security::syscall_filter)
+//! allow = ["@basic", "@file", "84"]   # or deny = [...]: groups or numbers
+//! action = "errno"         # "kill" (default), "errno" or "log"
+//! ```
+//!
+//! Only the subset of TOML used above is understood: sections, strings,
+//! integers, booleans and single-line string arrays. Unknown keys are
+//! ignored so newer unit files still load. Liveness checks, critical
+//! units and syscall filters are handled by the user-space init alone.
+
+use alloc::{string::String, vec::Vec};
+
+use super::{
+    init_system::{DependencyType, RestartPolicy, ServiceDefinition},
+    process_server::ResourceLimits,
+};
+use crate::error::KernelError;
+
+/// Directory holding the unit files.
+pub const UNIT_DIR: &str = "/etc/init";
+
+/// Unit file name suffix.
+pub const UNIT_SUFFIX: &str = ".toml";
+
+/// Longest service name; names travel in fixed-size IPC messages.
+pub const UNIT_NAME_MAX: usize = 31;
+
+/// Start level given to file-defined services: they start in the
+/// multi-user runlevels, ordered by their dependencies alone.
+const UNIT_START_LEVEL: u32 = 30;
+
+/// A parsed TOML value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Value {
+    pub(crate) fn int(&self) -> Result<u32, KernelError> {
+        match self {
+            Value::Int(n) => u32::try_from(*n).map_err(|_| invalid("integer out of range")),
+            _ => Err(invalid("expected an integer")),
+        }
+    }
+
+    pub(crate) fn string(self) -> Result<String, KernelError> {
+        match self {
+            Value::Str(s) => Ok(s),
+            _ => Err(invalid("expected a string")),
+        }
+    }
+
+    pub(crate) fn list(self) -> Result<Vec<String>, KernelError> {
+        match self {
+            Value::List(items) => Ok(items),
+            _ => Err(invalid("expected an array of strings")),
+        }
+    }
+}
+
+fn invalid(value: &'static str) -> KernelError {
+    KernelError::InvalidArgument {
+        name: "unit file",
+        value,
+    }
+}
+
+/// Parse a double-quoted string at the start of `s`, returning it and the
+/// rest of the input.
+fn parse_string(s: &str) -> Result<(String, &str), KernelError> {
+    let body = s.strip_prefix('"').ok_or(invalid("expected a string"))?;
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((out, &body[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => out.push('\n'),
+                Some((_, 't')) => out.push('\t'),
+                Some((_, c @ ('"' | '\\'))) => out.push(c),
+                _ => return Err(invalid("bad escape in string")),
+            },
+            c => out.push(c),
+        }
+    }
+    Err(invalid("unterminated string"))
+}
+
+/// Strip a trailing comment, which may only follow a value.
+fn end_of_line(rest: &str) -> Result<(), KernelError> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(invalid("trailing characters after value"))
+    }
+}
+
+fn parse_value(s: &str) -> Result<Value, KernelError> {
+    if s.starts_with('"') {
+        let (value, rest) = parse_string(s)?;
+        end_of_line(rest)?;
+        return Ok(Value::Str(value));
+    }
+    if let Some(mut rest) = s.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                end_of_line(after)?;
+                return Ok(Value::List(items));
+            }
+            let (item, after) = parse_string(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err(invalid("expected ',' or ']' in array"));
+            }
+        }
+    }
+    let token = s.split('#').next().unwrap_or("").trim();
+    match token {
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ => token
+            .replace('_', "")
+            .parse()
+            .map(Value::Int)
+            .map_err(|_| invalid("unsupported value")),
+    }
+}
+
+/// Walk the `key = value` lines of the TOML `text`, handing each to `entry`
+/// with the section it is in.
+pub(crate) fn parse_toml(
+    text: &str,
+    mut entry: impl FnMut(&str, &str, Value) -> Result<(), KernelError>,
+) -> Result<(), KernelError> {
+    let mut section = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let (header, rest) = header
+                .split_once(']')
+                .ok_or(invalid("unterminated section header"))?;
+            end_of_line(rest)?;
+            section = String::from(header.trim());
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(invalid("expected key = value"))?;
+        entry(&section, key.trim(), parse_value(value.trim())?)?;
+    }
+    Ok(())
+}
+
+/// Whether `name` is usable as a service name.
+pub fn valid_unit_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= UNIT_NAME_MAX
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'@'))
+}
+
+/// Parse the unit file `text` of service `name`.
+pub fn parse_unit(name: &str, text: &str) -> Result<ServiceDefinition, KernelError> {
+    if !valid_unit_name(name) {
+        return Err(invalid("bad service name"));
+    }
+    let mut def = ServiceDefinition {
+        name: String::from(name),
+        description: String::new(),
+        command: String::new(),
+        arguments: Vec::new(),
+        environment: Vec::new(),
+        working_directory: String::from("/"),
+        user: 0,
+        group: 0,
+        restart_policy: RestartPolicy::Never,
+        restart_delay_ms: 500,
+        max_restarts: 0,
+        timeout_ms: 10000,
+        dependencies: Vec::new(),
+        start_level: UNIT_START_LEVEL,
+        stop_timeout: None,
+        limits: ResourceLimits::unlimited(),
+    };
+
+    parse_toml(text, |section, key, value| {
+        match (section, key) {
+            ("unit", "description") => def.description = value.string()?,
+            ("unit", kind @ ("requires" | "after")) => {
+                let dep = if kind == "requires" {
+                    DependencyType::Requires
+                } else {
+                    DependencyType::After
+                };
+                for unit in value.list()? {
+                    if !valid_unit_name(&unit) {
+                        return Err(invalid("bad dependency name"));
+                    }
+                    def.dependencies.push((unit, dep));
+                }
+            }
+            ("service", "exec") => def.command = value.string()?,
+            ("service", "args") => def.arguments = value.list()?,
+            ("service", "env") => def.environment = value.list()?,
+            ("service", "dir") => def.working_directory = value.string()?,
+            ("service", "user") => def.user = value.int()?,
+            ("service", "group") => def.group = value.int()?,
+            ("service", "restart") => {
+                def.restart_policy = match value.string()?.as_str() {
+                    "no" => RestartPolicy::Never,
+                    "on-failure" => RestartPolicy::OnFailure,
+                    "always" => RestartPolicy::Always,
+                    _ => return Err(invalid("restart must be no, on-failure or always")),
+                }
+            }
+            ("service", "restart_delay_ms") => def.restart_delay_ms = value.int()?,
+            ("service", "max_restarts") => def.max_restarts = value.int()?,
+            ("service", "ready_timeout_ms") => def.timeout_ms = value.int()?,
+            ("service", "stop_timeout_s") => def.stop_timeout = Some(value.int()?),
+            ("service", "type") => match value.string()?.as_str() {
+                "simple" | "notify" => {}
+                _ => return Err(invalid("type must be simple or notify")),
+            },
+            ("limits", "memory_mb") => {
+                def.limits.max_memory = u64::from(value.int()?) * 1024 * 1024
+            }
+            ("limits", "cpu_s") => def.limits.max_cpu_time = u64::from(value.int()?) * 1_000_000,
+            ("limits", "open_files") => def.limits.max_files = value.int()?,
+            ("limits", "processes") => def.limits.max_processes = value.int()?,
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    if def.command.is_empty() {
+        return Err(invalid("missing [service] exec"));
+    }
+    Ok(def)
+}
+
+/// Load every unit in [`UNIT_DIR`]. Files that fail to parse are reported
+/// and skipped; a missing directory yields no services.
+pub fn load_units() -> Vec<ServiceDefinition> {
+    let mut units = Vec::new();
+    let Ok(dir) = crate::fs::get_vfs().read().resolve_path(UNIT_DIR) else {
+        return units;
+    };
+    let Ok(entries) = dir.readdir() else {
+        return units;
+    };
+    for entry in entries {
+        let Some(name) = entry.name.strip_suffix(UNIT_SUFFIX) else {
+            continue;
+        };
+        if entry.node_type != crate::fs::NodeType::File {
+            continue;
+        }
+        let path = alloc::format!("{}/{}", UNIT_DIR, entry.name);
+        let parsed = crate::fs::read_file(&path).and_then(|data| {
+            let text = core::str::from_utf8(&data).map_err(|_| invalid("not UTF-8"))?;
+            parse_unit(name, text)
+        });
+        match parsed {
+            Ok(def) => units.push(def),
+            Err(e) => crate::println!("[INIT] Skipping {}: {:?}", path, e),
+        }
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unit() {
+        let def = parse_unit(
+            "pkgd",
+            r#"
+# Package service
+[unit]
+description = "Package \"pkgd\" service"
+requires = ["network"]
+after = ["logger", "devmgr"]  # ordering only
+
+[service]
+exec = "/bin/pkgd"
+args = ["-v", "--root=/"]
+type = "notify"
+restart = "on-failure"
+restart_delay_ms = 1_000
+max_restarts = 3
+future_key = true
+
+[limits]
+memory_mb = 64
+open_files = 32
+"#,
+        )
+        .unwrap();
+        assert_eq!(def.name, "pkgd");
+        assert_eq!(def.description, "Package \"pkgd\" service");
+        assert_eq!(def.command, "/bin/pkgd");
+        assert_eq!(def.arguments, ["-v", "--root=/"]);
+        assert_eq!(def.restart_policy, RestartPolicy::OnFailure);
+        assert_eq!(def.restart_delay_ms, 1000);
+        assert_eq!(def.max_restarts, 3);
+        assert_eq!(def.limits.max_memory, 64 * 1024 * 1024);
+        assert_eq!(def.limits.max_files, 32);
+        assert_eq!(def.limits.max_cpu_time, u64::MAX);
+        assert_eq!(
+            def.dependencies,
+            [
+                (String::from("network"), DependencyType::Requires),
+                (String::from("logger"), DependencyType::After),
+                (String::from("devmgr"), DependencyType::After),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_unit_errors() {
+        assert!(parse_unit("x", "[service]\nargs = []\n").is_err());
+        assert!(parse_unit("x", "[service]\nexec = \"/bin/x\" junk\n").is_err());
+        assert!(parse_unit("x", "[service]\nexec = \"/bin/x\nrestart = \"no\"\n").is_err());
+        assert!(parse_unit(
+            "x",
+            "[service]\nexec = \"/bin/x\"\nrestart = \"sometimes\"\n"
+        )
+        .is_err());
+        assert!(parse_unit("x", "[service]\nexec = \"/bin/x\"\nuser = \"root\"\n").is_err());
+        assert!(parse_unit(
+            "x",
+            "[unit]\nrequires = [\"a b\"]\n[service]\nexec = \"/x\"\n"
+        )
+        .is_err());
+        assert!(parse_unit("bad name", "[service]\nexec = \"/bin/x\"\n").is_err());
+    }
+}
@@ -778,46 +778,45 @@ pub(crate) mod error {
 }
 
 /// Random number generation
+///
+/// Backed by the kernel CSPRNG, so results are unpredictable from boot and
+/// there is no shared seed for callers to stomp on.
 pub(crate) mod random {
-    use core::sync::atomic::{AtomicU32, Ordering};
-
-    static SEED: AtomicU32 = AtomicU32::new(1);
-
-    /// Set random seed
+    /// Mix a caller-provided seed into the CSPRNG.
+    ///
+    /// Unlike C `srand`, this does not make the sequence reproducible; the
+    /// seed is only ever added as extra input.
     pub(crate) fn srand(seed: u32) {
-        SEED.store(seed, Ordering::Relaxed);
+        crate::crypto::random::add_entropy(&seed.to_le_bytes());
     }
 
-    /// Generate random number
+    /// Generate random number in `0..=32767` (C `RAND_MAX`)
     pub(crate) fn rand() -> i32 {
-        let new_seed = SEED
-            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| {
-                Some(s.wrapping_mul(1103515245).wrapping_add(12345))
-            })
-            .unwrap_or(1);
-        let result = new_seed.wrapping_mul(1103515245).wrapping_add(12345);
-        (result / 65536) as i32 % 32768
+        (crate::crypto::random::get_random().next_u32() & 0x7FFF) as i32
     }
 
-    /// Generate random number in range
+    /// Generate random number in `min..max`
     pub(crate) fn rand_range(min: i32, max: i32) -> i32 {
         if min >= max {
             return min;
         }
-        min + (rand() % (max - min))
+        let span = (max as i64 - min as i64) as u64;
+        let offset = crate::crypto::random::get_random().next_u64() % span;
+        (min as i64 + offset as i64) as i32
     }
 }
 
 /// System information
 pub(crate) mod system {
-    use alloc::{collections::BTreeMap, string::String};
+    use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
 
     use spin::RwLock;
 
-    /// Global environment variable storage
+    /// Environment given to programs the kernel starts itself (init and
+    /// boot-time programs). Every other process inherits its parent's.
     static ENVIRONMENT: RwLock<Option<BTreeMap<String, String>>> = RwLock::new(None);
 
-    /// Initialize environment with default values
+    /// Initialize the default environment
     pub(crate) fn init_environment() {
         let mut env = BTreeMap::new();
         env.insert(
@@ -866,42 +865,33 @@ pub(crate) mod system {
         }
     }
 
+    /// Run `f` on the environment of the current process, or on the
+    /// default environment outside process context.
+    fn with_env<R>(f: impl FnOnce(&mut BTreeMap<String, String>) -> R) -> R {
+        if let Some(process) = crate::process::get_current_process() {
+            return f(&mut process.env_vars.lock());
+        }
+        f(ENVIRONMENT.write().get_or_insert_with(BTreeMap::new))
+    }
+
     /// Get environment variable
     ///
-    /// Retrieves the value of the specified environment variable.
-    /// Returns None if the variable is not set.
+    /// Retrieves the value of the specified environment variable of the
+    /// current process. Returns None if the variable is not set.
     pub(crate) fn getenv(name: &str) -> Option<String> {
-        let env_guard = ENVIRONMENT.read();
-        if let Some(ref env) = *env_guard {
-            env.get(name).cloned()
-        } else {
-            // Fallback defaults if environment not initialized
-            match name {
-                "PATH" => Some(String::from("/bin:/usr/bin")),
-                "HOME" => Some(String::from("/root")),
-                "USER" => Some(String::from("root")),
-                _ => None,
-            }
-        }
+        with_env(|env| env.get(name).cloned())
     }
 
     /// Set environment variable
     ///
     /// Sets the environment variable `name` to `value`.
-    /// Returns 0 on success, -1 on error.
+    /// Returns 0 on success, -1 if `name` is empty or contains `=`.
     pub(crate) fn setenv(name: &str, value: &str) -> i32 {
-        let mut env_guard = ENVIRONMENT.write();
-        if env_guard.is_none() {
-            // Initialize environment if not already done
-            *env_guard = Some(BTreeMap::new());
-        }
-
-        if let Some(ref mut env) = *env_guard {
-            env.insert(String::from(name), String::from(value));
-            0
-        } else {
-            -1
+        if name.is_empty() || name.contains('=') {
+            return -1;
         }
+        with_env(|env| env.insert(String::from(name), String::from(value)));
+        0
     }
 
     /// Unset environment variable
@@ -909,13 +899,8 @@ pub(crate) mod system {
     /// Removes the environment variable `name`.
     /// Returns 0 on success, -1 if variable doesn't exist.
     pub(crate) fn unsetenv(name: &str) -> i32 {
-        let mut env_guard = ENVIRONMENT.write();
-        if let Some(ref mut env) = *env_guard {
-            if env.remove(name).is_some() {
-                0
-            } else {
-                -1
-            }
+        if with_env(|env| env.remove(name)).is_some() {
+            0
         } else {
             -1
         }
@@ -925,11 +910,18 @@ pub(crate) mod system {
     ///
     /// Returns a copy of all environment variables as a BTreeMap.
     pub(crate) fn get_all_env() -> BTreeMap<String, String> {
-        let env_guard = ENVIRONMENT.read();
-        if let Some(ref env) = *env_guard {
-            env.clone()
-        } else {
-            BTreeMap::new()
+        with_env(|env| env.clone())
+    }
+
+    /// The default environment as `NAME=VALUE` strings, for the envp of a
+    /// program started by the kernel.
+    pub(crate) fn default_envp() -> Vec<String> {
+        match *ENVIRONMENT.read() {
+            Some(ref env) => env
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect(),
+            None => Vec::new(),
         }
     }
 }
@@ -0,0 +1,540 @@
+//! Lock Dependency Checker (lockdep)
+//!
+//! [`Mutex`] and [`RwLock`] are drop-in replacements for the `spin` locks.
+//! With the `lockdep` feature in a debug build they record the order in
+//! which lock classes are taken and report orderings that can deadlock:
+//!
+//! - taking lock B while holding A when A has been taken while holding B
+//!   before, directly or through other classes, and
+//! - taking a lock this CPU already holds, unless both are reads.
+//!
+//! A lock's class is the type it protects, so every `RwLock<Vfs>` belongs to
+//! one class; nesting two locks of the same class is not checked. Each
+//! problem is reported once, before the offending lock is taken, together
+//! with the locks held at the time. Otherwise the wrappers cost one byte per
+//! lock and nothing per acquisition.
+//!
+//! Held locks are tracked per CPU rather than per task, so a task preempted
+//! while holding a tracked lock can cause a false report on the CPU it left.
+//! For the same reason the scheduler locks, which are held across context
+//! switches, are not tracked.
+
+use core::{
+    any::type_name,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering},
+};
+
+use crate::sched::smp;
+
+/// Whether acquisitions are checked.
+const ENABLED: bool = cfg!(all(feature = "lockdep", debug_assertions));
+
+/// Maximum number of lock classes; locks of later classes are not tracked.
+const MAX_CLASSES: usize = 64;
+
+/// Maximum number of tracked locks one CPU can hold at once.
+const MAX_HELD: usize = 16;
+
+/// Class cache value before a lock's class has been looked up.
+const UNREGISTERED: u8 = u8::MAX;
+
+/// Class cache value for a lock whose class did not fit in the table.
+const UNTRACKED: u8 = u8::MAX - 1;
+
+// ---------------------------------------------------------------------------
+// Lock classes and the dependency graph
+// ---------------------------------------------------------------------------
+
+/// Class names, indexed by class ID. Only locked to register a class and to
+/// print a report.
+static CLASS_NAMES: spin::Mutex<[&str; MAX_CLASSES]> = spin::Mutex::new([""; MAX_CLASSES]);
+
+/// Number of registered classes.
+static NR_CLASSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Bit `b` of `DEPS[a]`: class `b` has been taken while class `a` was held.
+static DEPS: [AtomicU64; MAX_CLASSES] = [const { AtomicU64::new(0) }; MAX_CLASSES];
+
+/// Bit `b` of `REPORTED[a]`: taking `b` while holding `a` has been reported.
+static REPORTED: [AtomicU64; MAX_CLASSES] = [const { AtomicU64::new(0) }; MAX_CLASSES];
+
+/// Number of problems found so far.
+static VIOLATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Look up (registering if new) the class of a lock, caching it in the lock.
+fn class_of(cache: &AtomicU8, name: fn() -> &'static str) -> Option<u8> {
+    match cache.load(Ordering::Relaxed) {
+        UNREGISTERED => {}
+        UNTRACKED => return None,
+        class => return Some(class),
+    }
+    let class = register(name());
+    cache.store(class.unwrap_or(UNTRACKED), Ordering::Relaxed);
+    class
+}
+
+/// Class ID for `name`, or `None` once the table is full.
+fn register(name: &'static str) -> Option<u8> {
+    let mut names = CLASS_NAMES.lock();
+    let count = NR_CLASSES.load(Ordering::Acquire);
+    if let Some(class) = names[..count].iter().position(|&n| n == name) {
+        return Some(class as u8);
+    }
+    if count == MAX_CLASSES {
+        return None;
+    }
+    names[count] = name;
+    NR_CLASSES.store(count + 1, Ordering::Release);
+    Some(count as u8)
+}
+
+/// Whether class `to` has been taken while `from` was held, directly or
+/// through a chain of other classes.
+fn depends(from: u8, to: u8) -> bool {
+    let mut seen = 0u64;
+    let mut pending = 1u64 << from;
+    while pending != 0 {
+        let class = pending.trailing_zeros() as usize;
+        pending &= pending - 1;
+        seen |= 1 << class;
+        let next = DEPS[class].load(Ordering::Relaxed);
+        if next & (1 << to) != 0 {
+            return true;
+        }
+        pending |= next & !seen;
+    }
+    false
+}
+
+// ---------------------------------------------------------------------------
+// Held locks
+// ---------------------------------------------------------------------------
+
+/// A tracked lock held by a CPU.
+#[derive(Clone, Copy)]
+struct Entry {
+    class: u8,
+    /// Address of the lock, to tell instances of a class apart.
+    addr: usize,
+    exclusive: bool,
+}
+
+const NO_ENTRY: Entry = Entry {
+    class: 0,
+    addr: 0,
+    exclusive: false,
+};
+
+/// Locks held by one CPU, in acquisition order.
+struct HeldLocks {
+    entries: [Entry; MAX_HELD],
+    len: usize,
+}
+
+/// Each CPU only touches its own slot, with interrupts masked, so these
+/// locks are never contended.
+static HELD: [spin::Mutex<HeldLocks>; smp::MAX_CPUS] = [const {
+    spin::Mutex::new(HeldLocks {
+        entries: [NO_ENTRY; MAX_HELD],
+        len: 0,
+    })
+}; smp::MAX_CPUS];
+
+/// An ordering problem found when taking a lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Problem {
+    /// The lock is already held by this CPU.
+    Recursive,
+    /// This held class has been taken while the new lock's class was held.
+    Inversion(u8),
+}
+
+/// Check taking `new` while `held` are held and record the new
+/// dependencies.
+fn check(held: &[Entry], new: Entry) -> Option<Problem> {
+    let mut problem = None;
+    for entry in held {
+        if entry.addr == new.addr {
+            if entry.exclusive || new.exclusive {
+                problem = Some(Problem::Recursive);
+            }
+            continue;
+        }
+        if entry.class == new.class {
+            continue;
+        }
+        if problem.is_none() && depends(new.class, entry.class) {
+            let bit = 1 << new.class;
+            if REPORTED[entry.class as usize].fetch_or(bit, Ordering::Relaxed) & bit == 0 {
+                problem = Some(Problem::Inversion(entry.class));
+            }
+        }
+        DEPS[entry.class as usize].fetch_or(1 << new.class, Ordering::Relaxed);
+    }
+    problem
+}
+
+/// Record taking the lock at `addr`; `blocking` is false for `try_*`,
+/// which cannot deadlock and so is not checked.
+fn acquire(
+    cache: &AtomicU8,
+    name: fn() -> &'static str,
+    addr: usize,
+    exclusive: bool,
+    blocking: bool,
+) -> Held {
+    if !ENABLED {
+        return Held { addr: 0 };
+    }
+    let Some(class) = class_of(cache, name) else {
+        return Held { addr: 0 };
+    };
+    let new = Entry {
+        class,
+        addr,
+        exclusive,
+    };
+
+    let _irq = crate::arch::disable_interrupts();
+    let mut held = HELD[smp::current_cpu_id() as usize].lock();
+    let len = held.len;
+    let problem = if blocking {
+        check(&held.entries[..len], new)
+    } else {
+        None
+    };
+    let snapshot = (held.entries, len);
+    if len < MAX_HELD {
+        held.entries[len] = new;
+        held.len += 1;
+    }
+    drop(held);
+
+    if let Some(problem) = problem {
+        VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+        report(problem, new, &snapshot.0[..snapshot.1]);
+    }
+    Held { addr }
+}
+
+/// Forget the most recent acquisition of the lock at `addr` on this CPU.
+fn release(addr: usize) {
+    let _irq = crate::arch::disable_interrupts();
+    let mut held = HELD[smp::current_cpu_id() as usize].lock();
+    let len = held.len;
+    if let Some(index) = held.entries[..len].iter().rposition(|e| e.addr == addr) {
+        held.entries.copy_within(index + 1..len, index);
+        held.len -= 1;
+    }
+}
+
+fn report(problem: Problem, new: Entry, held: &[Entry]) {
+    let names = *CLASS_NAMES.lock();
+    let taking = names[new.class as usize];
+    match problem {
+        Problem::Recursive => {
+            crate::println!(
+                "[LOCKDEP] recursive locking of {} at {:#x}",
+                taking,
+                new.addr
+            );
+        }
+        Problem::Inversion(other) => {
+            let other = names[other as usize];
+            crate::println!(
+                "[LOCKDEP] possible deadlock: taking {} while holding {}",
+                taking,
+                other
+            );
+            crate::println!(
+                "[LOCKDEP]   {} has been taken while holding {} before",
+                other,
+                taking
+            );
+        }
+    }
+    for entry in held {
+        crate::println!(
+            "[LOCKDEP]   held: {} at {:#x}",
+            names[entry.class as usize],
+            entry.addr
+        );
+    }
+}
+
+/// Number of lock-ordering problems reported since boot.
+pub fn violations() -> usize {
+    VIOLATIONS.load(Ordering::Relaxed)
+}
+
+/// A tracked acquisition; dropped after the lock itself is released.
+struct Held {
+    /// Address of the lock, 0 if untracked.
+    addr: usize,
+}
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        if self.addr != 0 {
+            release(self.addr);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tracked locks
+// ---------------------------------------------------------------------------
+
+/// A `spin::Mutex` whose acquisitions are checked by lockdep.
+pub struct Mutex<T: ?Sized> {
+    class: AtomicU8,
+    inner: spin::Mutex<T>,
+}
+
+/// Guard for [`Mutex`].
+pub struct MutexGuard<'a, T: ?Sized> {
+    // Fields drop in declaration order: unlock, then stop tracking.
+    guard: spin::MutexGuard<'a, T>,
+    _held: Held,
+}
+
+impl<T> Mutex<T> {
+    /// Create an unlocked mutex.
+    pub const fn new(value: T) -> Self {
+        Self {
+            class: AtomicU8::new(UNREGISTERED),
+            inner: spin::Mutex::new(value),
+        }
+    }
+
+    /// Consume the mutex and return the value.
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    fn track(&self, blocking: bool) -> Held {
+        let addr = self as *const Self as *const () as usize;
+        acquire(&self.class, type_name::<T>, addr, true, blocking)
+    }
+
+    /// Lock the mutex, spinning until it is available.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        let held = self.track(true);
+        MutexGuard {
+            guard: self.inner.lock(),
+            _held: held,
+        }
+    }
+
+    /// Lock the mutex if it is free.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        let guard = self.inner.try_lock()?;
+        Some(MutexGuard {
+            guard,
+            _held: self.track(false),
+        })
+    }
+
+    /// Whether the mutex is locked.
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    /// Borrow the value through an exclusive reference, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+/// A `spin::RwLock` whose acquisitions are checked by lockdep.
+pub struct RwLock<T: ?Sized> {
+    class: AtomicU8,
+    inner: spin::RwLock<T>,
+}
+
+/// Shared guard for [`RwLock`].
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    guard: spin::RwLockReadGuard<'a, T>,
+    _held: Held,
+}
+
+/// Exclusive guard for [`RwLock`].
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    guard: spin::RwLockWriteGuard<'a, T>,
+    _held: Held,
+}
+
+impl<T> RwLock<T> {
+    /// Create an unlocked lock.
+    pub const fn new(value: T) -> Self {
+        Self {
+            class: AtomicU8::new(UNREGISTERED),
+            inner: spin::RwLock::new(value),
+        }
+    }
+
+    /// Consume the lock and return the value.
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    fn track(&self, exclusive: bool, blocking: bool) -> Held {
+        let addr = self as *const Self as *const () as usize;
+        acquire(&self.class, type_name::<T>, addr, exclusive, blocking)
+    }
+
+    /// Take a shared lock, spinning until no writer holds it.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        let held = self.track(false, true);
+        RwLockReadGuard {
+            guard: self.inner.read(),
+            _held: held,
+        }
+    }
+
+    /// Take the exclusive lock, spinning until it is free.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        let held = self.track(true, true);
+        RwLockWriteGuard {
+            guard: self.inner.write(),
+            _held: held,
+        }
+    }
+
+    /// Take a shared lock if no writer holds it.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        let guard = self.inner.try_read()?;
+        Some(RwLockReadGuard {
+            guard,
+            _held: self.track(false, false),
+        })
+    }
+
+    /// Take the exclusive lock if it is free.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        let guard = self.inner.try_write()?;
+        Some(RwLockWriteGuard {
+            guard,
+            _held: self.track(true, false),
+        })
+    }
+
+    /// Borrow the value through an exclusive reference, without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(class: u8, addr: usize, exclusive: bool) -> Entry {
+        Entry {
+            class,
+            addr,
+            exclusive,
+        }
+    }
+
+    #[test]
+    fn test_register_dedups() {
+        let a = register("lockdep-test-a").unwrap();
+        assert_eq!(register("lockdep-test-a"), Some(a));
+        assert_ne!(register("lockdep-test-b"), Some(a));
+    }
+
+    #[test]
+    fn test_inversion_reported_once() {
+        let a = register("lockdep-test-inv-a").unwrap();
+        let b = register("lockdep-test-inv-b").unwrap();
+        let c = register("lockdep-test-inv-c").unwrap();
+
+        // a -> b, then b -> c: c while holding a is fine, a while holding c
+        // is not.
+        assert_eq!(check(&[entry(a, 0x10, true)], entry(b, 0x20, true)), None);
+        assert_eq!(check(&[entry(b, 0x20, true)], entry(c, 0x30, true)), None);
+        assert!(depends(a, c));
+        assert_eq!(
+            check(&[entry(c, 0x30, true)], entry(a, 0x10, true)),
+            Some(Problem::Inversion(c))
+        );
+        assert_eq!(check(&[entry(c, 0x30, true)], entry(a, 0x10, true)), None);
+    }
+
+    #[test]
+    fn test_recursive_locking() {
+        let a = register("lockdep-test-rec").unwrap();
+        let held = [entry(a, 0x40, false)];
+        assert_eq!(check(&held, entry(a, 0x40, false)), None);
+        assert_eq!(check(&held, entry(a, 0x40, true)), Some(Problem::Recursive));
+        // Another lock of the same class is not checked.
+        assert_eq!(check(&held, entry(a, 0x50, true)), None);
+    }
+
+    #[test]
+    fn test_tracked_locks() {
+        let lock = RwLock::new(1u32);
+        assert_eq!(*lock.read(), 1);
+        *lock.write() += 1;
+        assert_eq!(lock.try_read().map(|v| *v), Some(2));
+        let mutex = Mutex::new(0u8);
+        let guard = mutex.lock();
+        assert!(mutex.try_lock().is_none());
+        drop(guard);
+        assert!(!mutex.is_locked());
+    }
+}
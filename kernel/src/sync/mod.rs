@@ -6,8 +6,12 @@
 //! - RCU (Read-Copy-Update) for read-heavy data structures
 //! - Hazard pointers for safe memory reclamation
 //! - Lock-free MPSC queue for scheduler ready queues
+//!
+//! [`lockdep`] provides drop-in `Mutex`/`RwLock` wrappers that check lock
+//! ordering in debug builds with the `lockdep` feature.
 
 pub mod hazard;
+pub mod lockdep;
 pub mod lockfree_queue;
 pub mod once_lock;
 pub mod rcu;
@@ -13,14 +13,26 @@
 //! - Writers call `synchronize_rcu()` to wait for all pre-existing readers to
 //!   complete, or `call_rcu()` to defer cleanup to a callback.
 //! - Grace period detection uses per-CPU counters: when all CPUs have passed
-//!   through a quiescent state (context switch or explicit `rcu_quiescent()`),
-//!   the grace period is complete.
+//!   through a quiescent state (context switch, timer tick or explicit
+//!   `rcu_quiescent()`), the grace period is complete.
+//! - Read-side critical sections are tied to scheduling: the timer tick does
+//!   not preempt a task inside one, so a section never migrates between CPUs
+//!   and may not sleep.
+//!
+//! [`Rcu`] wraps a value in this scheme: readers borrow the current version
+//! through a guard, writers publish a modified copy.
 
 use alloc::{boxed::Box, vec::Vec};
-use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering},
+};
 
 use spin::Mutex;
 
+use crate::sched::workqueue::{self, Work};
+
 // ---------------------------------------------------------------------------
 // Per-CPU RCU state
 // ---------------------------------------------------------------------------
@@ -28,29 +40,33 @@ use spin::Mutex;
 /// Maximum number of CPUs for RCU tracking.
 const RCU_MAX_CPUS: usize = 16;
 
-/// Per-CPU nesting depth of RCU read-side critical sections.
-///
-/// When > 0, the CPU is inside an RCU read-side critical section and
-/// cannot be considered quiescent.
-#[allow(clippy::declare_interior_mutable_const)]
-static RCU_NESTING: [AtomicUsize; RCU_MAX_CPUS] = {
-    const INIT: AtomicUsize = AtomicUsize::new(0);
-    [INIT; RCU_MAX_CPUS]
-};
+/// RCU state of one CPU, padded to a cache line so that readers on
+/// different CPUs never write to the same line.
+#[repr(align(64))]
+struct RcuCpu {
+    /// Nesting depth of RCU read-side critical sections. While > 0 the CPU
+    /// cannot be considered quiescent.
+    nesting: AtomicUsize,
+    /// Last grace period this CPU observed in a quiescent state.
+    gp: AtomicU64,
+}
+
+impl RcuCpu {
+    const fn new() -> Self {
+        Self {
+            nesting: AtomicUsize::new(0),
+            gp: AtomicU64::new(0),
+        }
+    }
+}
+
+static RCU_CPUS: [RcuCpu; RCU_MAX_CPUS] = [const { RcuCpu::new() }; RCU_MAX_CPUS];
 
 /// Global RCU grace period counter. Incremented each time a grace period
-/// completes. Writers snapshot this before waiting; when all CPUs have
+/// starts. Writers snapshot this before waiting; when all CPUs have
 /// observed a quiescent state since the snapshot, the grace period is done.
 static RCU_GP_COUNTER: AtomicU64 = AtomicU64::new(0);
 
-/// Per-CPU last-observed grace period. Updated each time a CPU passes
-/// through a quiescent state.
-#[allow(clippy::declare_interior_mutable_const)]
-static RCU_CPU_GP: [AtomicU64; RCU_MAX_CPUS] = {
-    const INIT: AtomicU64 = AtomicU64::new(0);
-    [INIT; RCU_MAX_CPUS]
-};
-
 // ---------------------------------------------------------------------------
 // Deferred callback queue
 // ---------------------------------------------------------------------------
@@ -78,22 +94,23 @@ static RCU_CALLBACKS: Mutex<Vec<RcuCallback>> = Mutex::new(Vec::new());
 /// Must be paired with `rcu_read_unlock()`. Nesting is supported.
 #[inline]
 pub fn rcu_read_lock() {
-    let cpu = current_cpu();
-    RCU_NESTING[cpu].fetch_add(1, Ordering::Relaxed);
+    RCU_CPUS[current_cpu()]
+        .nesting
+        .fetch_add(1, Ordering::Relaxed);
 }
 
 /// Exit an RCU read-side critical section.
 #[inline]
 pub fn rcu_read_unlock() {
-    let cpu = current_cpu();
-    let prev = RCU_NESTING[cpu].fetch_sub(1, Ordering::Relaxed);
+    let prev = RCU_CPUS[current_cpu()]
+        .nesting
+        .fetch_sub(1, Ordering::Relaxed);
     debug_assert!(prev > 0, "rcu_read_unlock without matching rcu_read_lock");
 }
 
 /// Check whether the current CPU is inside an RCU read-side critical section.
 pub fn rcu_is_reading() -> bool {
-    let cpu = current_cpu();
-    RCU_NESTING[cpu].load(Ordering::Relaxed) > 0
+    RCU_CPUS[current_cpu()].nesting.load(Ordering::Relaxed) > 0
 }
 
 // ---------------------------------------------------------------------------
@@ -105,37 +122,23 @@ pub fn rcu_is_reading() -> bool {
 /// After this function returns, it is safe to free memory that was visible
 /// to readers before the call. This is the synchronous grace period wait.
 ///
-/// On a single-CPU system, a single quiescent state check is sufficient.
-/// On SMP, we spin until all CPUs have reported a quiescent state.
+/// Other CPUs report quiescent states on context switches and timer ticks,
+/// so on SMP this spins for up to a tick. Must not be called inside a
+/// read-side critical section; prefer `call_rcu()` where waiting is not
+/// needed.
 pub fn synchronize_rcu() {
+    debug_assert!(
+        !rcu_is_reading(),
+        "synchronize_rcu inside an RCU read-side critical section"
+    );
     let target_gp = RCU_GP_COUNTER.fetch_add(1, Ordering::SeqCst) + 1;
 
-    // Wait for all CPUs to observe the new grace period.
-    // On a single-CPU system, if we're not in an RCU read section, we're
-    // already past a quiescent point.
+    // This CPU is not reading, so it is quiescent already.
     loop {
-        let mut all_quiescent = true;
-        for cpu in 0..RCU_MAX_CPUS {
-            // A CPU is quiescent if it has no active read-side sections
-            // AND has observed the current grace period.
-            let nesting = RCU_NESTING[cpu].load(Ordering::Acquire);
-            let cpu_gp = RCU_CPU_GP[cpu].load(Ordering::Acquire);
-
-            if nesting > 0 || cpu_gp < target_gp {
-                // CPU 0 is always online; others may not exist.
-                // If a CPU doesn't exist, it's vacuously quiescent.
-                if cpu == 0 || crate::sched::smp::per_cpu(cpu as u8).is_some() {
-                    all_quiescent = false;
-                    break;
-                }
-            }
-        }
-
-        if all_quiescent {
+        rcu_quiescent();
+        if grace_period_done(target_gp) {
             break;
         }
-
-        // Yield to other tasks while waiting.
         core::hint::spin_loop();
     }
 
@@ -143,18 +146,63 @@ pub fn synchronize_rcu() {
     process_callbacks(target_gp);
 }
 
+/// Whether every CPU has passed through a quiescent state since grace
+/// period `target_gp` started.
+fn grace_period_done(target_gp: u64) -> bool {
+    RCU_CPUS.iter().enumerate().all(|(cpu, state)| {
+        // A CPU is quiescent if it has no active read-side sections AND
+        // has observed the grace period. CPU 0 is always online; a CPU that
+        // doesn't exist is vacuously quiescent.
+        let quiescent = state.nesting.load(Ordering::Acquire) == 0
+            && state.gp.load(Ordering::Acquire) >= target_gp;
+        quiescent || (cpu != 0 && crate::sched::smp::per_cpu(cpu as u8).is_none())
+    })
+}
+
 /// Register a deferred callback to be called after the next grace period.
 ///
 /// The callback will be invoked after all CPUs have passed through a
 /// quiescent state following this call. The callback must be `Send`
-/// since it may execute on a different CPU.
+/// since it may execute on a different CPU. Callbacks run from a work
+/// item (see [`crate::sched::workqueue`]), so `call_rcu()` never waits.
 pub fn call_rcu<F: FnOnce() + Send + 'static>(func: F) {
-    let target_gp = RCU_GP_COUNTER.load(Ordering::Relaxed) + 1;
-    let mut callbacks = RCU_CALLBACKS.lock();
-    callbacks.push(RcuCallback {
+    let target_gp = RCU_GP_COUNTER.load(Ordering::Acquire) + 1;
+    RCU_CALLBACKS.lock().push(RcuCallback {
         target_gp,
         func: Box::new(func),
     });
+    workqueue::queue_work(&RECLAIM_WORK);
+}
+
+/// Runs `call_rcu()` callbacks once their grace period has passed.
+static RECLAIM_WORK: Work = Work::new(reclaim, 0);
+
+/// Grace period the reclaim work is waiting for, 0 if none.
+static RECLAIM_GP: AtomicU64 = AtomicU64::new(0);
+
+/// Start a grace period for the queued callbacks and poll it once per tick
+/// until it completes, instead of spinning in a kworker. Work items never
+/// run concurrently with themselves, so `RECLAIM_GP` has a single writer.
+fn reclaim(_data: usize) {
+    let mut target_gp = RECLAIM_GP.load(Ordering::Acquire);
+    if target_gp == 0 {
+        target_gp = RCU_GP_COUNTER.fetch_add(1, Ordering::SeqCst) + 1;
+        RECLAIM_GP.store(target_gp, Ordering::Release);
+    }
+
+    rcu_quiescent();
+    if !grace_period_done(target_gp) {
+        workqueue::queue_delayed_work(&RECLAIM_WORK, 1);
+        return;
+    }
+
+    RECLAIM_GP.store(0, Ordering::Release);
+    process_callbacks(target_gp);
+
+    // Callbacks queued during this grace period need another one.
+    if !RCU_CALLBACKS.lock().is_empty() {
+        workqueue::queue_work(&RECLAIM_WORK);
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -163,15 +211,14 @@ pub fn call_rcu<F: FnOnce() + Send + 'static>(func: F) {
 
 /// Report that the current CPU has passed through a quiescent state.
 ///
-/// Called from the scheduler during context switch, timer tick, or
-/// explicit idle. A CPU in a quiescent state is not holding any RCU
-/// read-side references from before the current grace period.
+/// Called from the scheduler during context switch and timer tick. A CPU
+/// in a quiescent state is not holding any RCU read-side references from
+/// before the current grace period.
 pub fn rcu_quiescent() {
-    let cpu = current_cpu();
-    let nesting = RCU_NESTING[cpu].load(Ordering::Relaxed);
-    if nesting == 0 {
+    let state = &RCU_CPUS[current_cpu()];
+    if state.nesting.load(Ordering::Relaxed) == 0 {
         let current_gp = RCU_GP_COUNTER.load(Ordering::Acquire);
-        RCU_CPU_GP[cpu].store(current_gp, Ordering::Release);
+        state.gp.store(current_gp, Ordering::Release);
     }
 }
 
@@ -193,6 +240,116 @@ fn process_callbacks(completed_gp: u64) {
     }
 }
 
+// ---------------------------------------------------------------------------
+// RCU-protected pointer
+// ---------------------------------------------------------------------------
+
+/// A value that readers access without locking and writers replace
+/// wholesale.
+///
+/// Readers call [`Rcu::read`] and get a guard that keeps the version they
+/// saw alive. Writers copy the current version, change the copy and publish
+/// it with [`Rcu::update`]; the old version is freed after a grace period.
+/// Writers are serialized among themselves, so updates are never lost, but
+/// never block readers. Suited to small, read-mostly tables: every update
+/// copies the whole value.
+pub struct Rcu<T> {
+    ptr: AtomicPtr<T>,
+    writer: Mutex<()>,
+}
+
+// SAFETY: readers on any CPU get `&T` and the last version may be dropped
+// on any CPU, so `T` must be `Send + Sync`.
+unsafe impl<T: Send + Sync> Send for Rcu<T> {}
+// SAFETY: as above.
+unsafe impl<T: Send + Sync> Sync for Rcu<T> {}
+
+impl<T: Send + Sync + 'static> Rcu<T> {
+    /// Create a cell holding `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(value))),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Enter a read-side critical section and borrow the current version.
+    ///
+    /// The guard must not be held across anything that sleeps: the task is
+    /// not preempted while it holds one, and grace periods stall until it
+    /// is dropped.
+    pub fn read(&self) -> RcuGuard<'_, T> {
+        rcu_read_lock();
+        // SAFETY: the pointer always refers to a live version; a replaced
+        // version is freed only after every read section that could have
+        // loaded it has ended, and this one has begun.
+        let value = unsafe { &*self.ptr.load(Ordering::Acquire) };
+        RcuGuard {
+            value,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Publish `value` as the new version.
+    pub fn replace(&self, value: T) {
+        let _writer = self.writer.lock();
+        self.publish(Box::new(value));
+    }
+
+    /// Copy the current version, apply `f` to the copy and publish it.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        T: Clone,
+    {
+        let _writer = self.writer.lock();
+        // SAFETY: holding the writer lock, the current version cannot be
+        // replaced (and so freed) under us.
+        let mut copy = Box::new(unsafe { (*self.ptr.load(Ordering::Acquire)).clone() });
+        let result = f(&mut copy);
+        self.publish(copy);
+        result
+    }
+
+    fn publish(&self, value: Box<T>) {
+        let old = self.ptr.swap(Box::into_raw(value), Ordering::AcqRel) as usize;
+        call_rcu(move || {
+            // SAFETY: `old` came from `Box::into_raw` and is no longer
+            // reachable: it was unpublished a grace period ago.
+            drop(unsafe { Box::from_raw(old as *mut T) });
+        });
+    }
+}
+
+impl<T> Drop for Rcu<T> {
+    fn drop(&mut self) {
+        // SAFETY: no guard can outlive `&self`, so nobody can still be
+        // reading the current version.
+        drop(unsafe { Box::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+/// A read-side critical section over one version of an [`Rcu`] value.
+///
+/// Not `Send`: the section belongs to the CPU it was entered on.
+pub struct RcuGuard<'a, T> {
+    value: &'a T,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<T> Deref for RcuGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> Drop for RcuGuard<'_, T> {
+    fn drop(&mut self) {
+        rcu_read_unlock();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -201,3 +358,40 @@ fn process_callbacks(completed_gp: u64) {
 fn current_cpu() -> usize {
     crate::sched::smp::current_cpu_id() as usize
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeMap, sync::Arc};
+
+    use super::*;
+
+    #[test]
+    fn test_rcu_update() {
+        let cell = Rcu::new(BTreeMap::new());
+        assert_eq!(cell.update(|map| map.insert(1u32, 10u32)), None);
+        assert_eq!(cell.read().get(&1), Some(&10));
+        cell.replace(BTreeMap::new());
+        assert!(cell.read().is_empty());
+    }
+
+    #[test]
+    fn test_rcu_guard_keeps_old_version() {
+        let cell = Rcu::new(1u32);
+        let old = cell.read();
+        cell.replace(2);
+        assert_eq!(*old, 1);
+        assert!(rcu_is_reading());
+        drop(old);
+        assert!(!rcu_is_reading());
+        assert_eq!(*cell.read(), 2);
+    }
+
+    #[test]
+    fn test_synchronize_runs_callbacks() {
+        let value = Arc::new(());
+        let held = value.clone();
+        call_rcu(move || drop(held));
+        synchronize_rcu();
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
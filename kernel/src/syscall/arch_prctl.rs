@@ -72,13 +72,7 @@ pub fn sys_arch_prctl(code: usize, addr: usize) -> Result<isize, SyscallError> {
             Ok(0)
         }
         ARCH_GET_FS => {
-            // SAFETY: `copy_to_user` validates that `addr` points to a
-            // mapped, writable, user-space region large enough for a u64.
-            // The `tls_base()` value is a plain integer read from the
-            // thread context and does not involve any unsafe memory access
-            // itself.
-            unsafe { crate::syscall::userspace::copy_to_user(addr, &ctx.tls_base()) }
-                .map_err(|_| SyscallError::InvalidPointer)?;
+            crate::syscall::uaccess::copy_to_user(addr, &ctx.tls_base())?;
             Ok(0)
         }
         _ => Err(SyscallError::InvalidArgument),
@@ -0,0 +1,363 @@
+//! Cryptographic primitive syscalls.
+//!
+//! Exposes the kernel's hash, HMAC, AEAD, X25519, signature verification and
+//! Ed25519 signing implementations so that user-space protocol code (the TLS
+//! client in veridian-std, vsshd) runs on the same primitives as the kernel
+//! TLS stack instead of carrying its own copies. Calls are stateless: every
+//! input is copied into kernel memory before use and every result is copied
+//! out.
+//!
+//! Calls with more buffers than fit in registers take a pointer to a
+//! `#[repr(C)]` argument block; the layouts below are mirrored in
+//! `userland/rust-std/src/sys/veridian/crypto.rs`.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use super::{
+    uaccess::{copy_bytes_to_user, copy_from_user, copy_slice_from_user},
+    SyscallError, SyscallResult,
+};
+use crate::crypto::{
+    asymmetric::{x25519_scalar_mult, KeyPair},
+    constant_time::ct_zero,
+    hash::{self, HashAlgorithm},
+    signature::{self, SignatureScheme},
+};
+// The AEAD ciphers are the TLS record layer's
+#[cfg(feature = "net")]
+use crate::net::tls::{cipher, CipherSuite};
+
+/// Largest single input accepted by any crypto call (1 MiB)
+const MAX_CRYPTO_INPUT: usize = 1024 * 1024;
+
+/// Hash algorithm identifiers for SYS_CRYPTO_HASH / SYS_CRYPTO_HMAC
+pub const CRYPTO_HASH_SHA256: usize = 1;
+pub const CRYPTO_HASH_SHA384: usize = 2;
+pub const CRYPTO_HASH_SHA512: usize = 3;
+
+/// AEAD identifiers for SYS_CRYPTO_AEAD_SEAL / SYS_CRYPTO_AEAD_OPEN
+pub const CRYPTO_AEAD_AES128_GCM: usize = 1;
+pub const CRYPTO_AEAD_CHACHA20_POLY1305: usize = 2;
+
+/// AEAD authentication tag length
+#[cfg(feature = "net")]
+const AEAD_TAG_LEN: usize = 16;
+
+/// Argument block for SYS_CRYPTO_HMAC.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CryptoHmacArgs {
+    pub key: usize,
+    pub key_len: usize,
+    pub data: usize,
+    pub data_len: usize,
+    pub out: usize,
+    pub out_len: usize,
+}
+
+/// Argument block for SYS_CRYPTO_AEAD_SEAL / SYS_CRYPTO_AEAD_OPEN.
+///
+/// `input` is the plaintext when sealing and ciphertext-plus-tag when
+/// opening; `output` must hold `input_len + 16` and `input_len - 16` bytes
+/// respectively.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CryptoAeadArgs {
+    pub key: usize,
+    pub key_len: usize,
+    pub nonce: usize,
+    pub nonce_len: usize,
+    pub aad: usize,
+    pub aad_len: usize,
+    pub input: usize,
+    pub input_len: usize,
+    pub output: usize,
+    pub output_len: usize,
+}
+
+/// Argument block for SYS_CRYPTO_VERIFY.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CryptoVerifyArgs {
+    pub key: usize,
+    pub key_len: usize,
+    pub message: usize,
+    pub message_len: usize,
+    pub signature: usize,
+    pub signature_len: usize,
+}
+
+/// Argument block for SYS_CRYPTO_SIGN.
+///
+/// `public_key` may be 0; otherwise the signer's public key is written
+/// there as well.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CryptoSignArgs {
+    pub key: usize,
+    pub key_len: usize,
+    pub message: usize,
+    pub message_len: usize,
+    pub signature: usize,
+    pub signature_len: usize,
+    pub public_key: usize,
+    pub public_key_len: usize,
+}
+
+fn hash_algorithm(alg: usize) -> Result<HashAlgorithm, SyscallError> {
+    match alg {
+        CRYPTO_HASH_SHA256 => Ok(HashAlgorithm::Sha256),
+        CRYPTO_HASH_SHA384 => Ok(HashAlgorithm::Sha384),
+        CRYPTO_HASH_SHA512 => Ok(HashAlgorithm::Sha512),
+        _ => Err(SyscallError::InvalidArgument),
+    }
+}
+
+/// Copy `len` bytes from user space into a kernel buffer.
+fn copy_in(ptr: usize, len: usize) -> Result<Vec<u8>, SyscallError> {
+    if len > MAX_CRYPTO_INPUT {
+        return Err(SyscallError::InvalidArgument);
+    }
+    copy_slice_from_user(ptr, len)
+}
+
+/// Copy `data` out to a user buffer of capacity `cap`.
+fn copy_out(ptr: usize, cap: usize, data: &[u8]) -> SyscallResult {
+    if cap < data.len() {
+        return Err(SyscallError::InvalidArgument);
+    }
+    copy_bytes_to_user(ptr, data)?;
+    Ok(data.len())
+}
+
+/// Read a `#[repr(C)]` argument block from user space.
+fn read_args<T: Copy>(ptr: usize) -> Result<T, SyscallError> {
+    copy_from_user(ptr)
+}
+
+/// Hash a buffer (SYS_CRYPTO_HASH = 363).
+///
+/// # Arguments
+/// - `alg`: `CRYPTO_HASH_*` identifier.
+/// - `data`, `len`: Input buffer.
+/// - `out`, `out_len`: Digest buffer (32, 48 or 64 bytes).
+///
+/// # Returns
+/// Digest length in bytes.
+pub fn sys_crypto_hash(
+    alg: usize,
+    data: usize,
+    len: usize,
+    out: usize,
+    out_len: usize,
+) -> SyscallResult {
+    let alg = hash_algorithm(alg)?;
+    let input = copy_in(data, len)?;
+    let digest = hash::hash(alg, &input).map_err(|_| SyscallError::InvalidArgument)?;
+    copy_out(out, out_len, &digest)
+}
+
+/// Compute an HMAC (SYS_CRYPTO_HMAC = 364).
+///
+/// # Arguments
+/// - `alg`: `CRYPTO_HASH_*` identifier.
+/// - `args`: Pointer to a [`CryptoHmacArgs`].
+///
+/// # Returns
+/// MAC length in bytes.
+pub fn sys_crypto_hmac(alg: usize, args: usize) -> SyscallResult {
+    let alg = hash_algorithm(alg)?;
+    let args: CryptoHmacArgs = read_args(args)?;
+    let mut key = copy_in(args.key, args.key_len)?;
+    let data = copy_in(args.data, args.data_len)?;
+    let mac = hash::hmac(alg, &key, &data);
+    ct_zero(&mut key);
+    let mac = mac.map_err(|_| SyscallError::InvalidArgument)?;
+    copy_out(args.out, args.out_len, &mac)
+}
+
+/// Kernel copies of an AEAD call's inputs.
+#[cfg(feature = "net")]
+struct AeadInputs {
+    suite: CipherSuite,
+    key: Vec<u8>,
+    nonce: [u8; 12],
+    aad: Vec<u8>,
+    input: Vec<u8>,
+}
+
+/// Validate and copy in the key, nonce, AAD and input of an AEAD call.
+#[cfg(feature = "net")]
+fn aead_inputs(alg: usize, args: &CryptoAeadArgs) -> Result<AeadInputs, SyscallError> {
+    let suite = match alg {
+        CRYPTO_AEAD_AES128_GCM => CipherSuite::Aes128GcmSha256,
+        CRYPTO_AEAD_CHACHA20_POLY1305 => CipherSuite::ChaCha20Poly1305Sha256,
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+    if args.key_len != suite.key_len() || args.nonce_len != 12 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let key = copy_in(args.key, args.key_len)?;
+    let mut nonce = [0u8; 12];
+    nonce.copy_from_slice(&copy_in(args.nonce, args.nonce_len)?);
+    Ok(AeadInputs {
+        suite,
+        key,
+        nonce,
+        aad: copy_in(args.aad, args.aad_len)?,
+        input: copy_in(args.input, args.input_len)?,
+    })
+}
+
+/// Encrypt and authenticate (SYS_CRYPTO_AEAD_SEAL = 365).
+///
+/// # Arguments
+/// - `alg`: `CRYPTO_AEAD_*` identifier.
+/// - `args`: Pointer to a [`CryptoAeadArgs`].
+///
+/// # Returns
+/// Ciphertext length including the 16-byte tag.
+#[cfg(feature = "net")]
+pub fn sys_crypto_aead_seal(alg: usize, args: usize) -> SyscallResult {
+    let args: CryptoAeadArgs = read_args(args)?;
+    let mut inputs = aead_inputs(alg, &args)?;
+    let sealed = cipher::aead_encrypt(
+        inputs.suite,
+        &inputs.key,
+        &inputs.nonce,
+        &inputs.aad,
+        &inputs.input,
+    );
+    ct_zero(&mut inputs.key);
+    ct_zero(&mut inputs.input);
+    let sealed = sealed.ok_or(SyscallError::InvalidArgument)?;
+    copy_out(args.output, args.output_len, &sealed)
+}
+
+/// Authenticate and decrypt (SYS_CRYPTO_AEAD_OPEN = 366).
+///
+/// # Arguments
+/// - `alg`: `CRYPTO_AEAD_*` identifier.
+/// - `args`: Pointer to a [`CryptoAeadArgs`].
+///
+/// # Returns
+/// Plaintext length, or `PermissionDenied` if the tag does not verify.
+#[cfg(feature = "net")]
+pub fn sys_crypto_aead_open(alg: usize, args: usize) -> SyscallResult {
+    let args: CryptoAeadArgs = read_args(args)?;
+    if args.input_len < AEAD_TAG_LEN {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let mut inputs = aead_inputs(alg, &args)?;
+    let opened = cipher::aead_decrypt(
+        inputs.suite,
+        &inputs.key,
+        &inputs.nonce,
+        &inputs.aad,
+        &inputs.input,
+    );
+    ct_zero(&mut inputs.key);
+    let mut plaintext = opened.ok_or(SyscallError::PermissionDenied)?;
+    let result = copy_out(args.output, args.output_len, &plaintext);
+    ct_zero(&mut plaintext);
+    result
+}
+
+/// X25519 Diffie-Hellman (SYS_CRYPTO_X25519 = 367).
+///
+/// # Arguments
+/// - `scalar`: 32-byte private scalar (clamped by the kernel).
+/// - `point`: 32-byte peer u-coordinate, or the base point 9 to derive a public
+///   key.
+/// - `out`: 32-byte result buffer.
+///
+/// # Returns
+/// 0 on success; `InvalidArgument` if the result is all zeros (a
+/// small-order peer point, RFC 7748 section 6.1).
+pub fn sys_crypto_x25519(scalar: usize, point: usize, out: usize) -> SyscallResult {
+    let mut k = [0u8; 32];
+    k.copy_from_slice(&copy_in(scalar, 32)?);
+    let mut u = [0u8; 32];
+    u.copy_from_slice(&copy_in(point, 32)?);
+
+    let mut shared = x25519_scalar_mult(&k, &u);
+    ct_zero(&mut k);
+    if shared.iter().all(|&b| b == 0) {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let result = copy_out(out, 32, &shared);
+    ct_zero(&mut shared);
+    result.map(|_| 0)
+}
+
+/// Verify a signature (SYS_CRYPTO_VERIFY = 368).
+///
+/// # Arguments
+/// - `scheme`: TLS 1.3 `SignatureScheme` code point; see
+///   [`crate::crypto::signature`] for the key formats.
+/// - `args`: Pointer to a [`CryptoVerifyArgs`].
+///
+/// # Returns
+/// 1 if the signature is valid, 0 if it is not.
+pub fn sys_crypto_verify(scheme: usize, args: usize) -> SyscallResult {
+    let scheme = u16::try_from(scheme)
+        .ok()
+        .and_then(SignatureScheme::from_code)
+        .ok_or(SyscallError::InvalidArgument)?;
+    let args: CryptoVerifyArgs = read_args(args)?;
+    let key = copy_in(args.key, args.key_len)?;
+    let message = copy_in(args.message, args.message_len)?;
+    let sig = copy_in(args.signature, args.signature_len)?;
+
+    match signature::verify(scheme, &key, &message, &sig) {
+        Ok(valid) => Ok(valid as usize),
+        Err(_) => Err(SyscallError::InvalidArgument),
+    }
+}
+
+/// Sign a message (SYS_CRYPTO_SIGN = 369).
+///
+/// Only Ed25519 (0x0807) is supported; the key is the 32-byte RFC 8032
+/// seed. Supplying a `public_key` buffer also returns the seed's public
+/// key, which is how a newly generated host key gets its public half.
+///
+/// # Arguments
+/// - `scheme`: TLS 1.3 `SignatureScheme` code point.
+/// - `args`: Pointer to a [`CryptoSignArgs`].
+///
+/// # Returns
+/// Signature length in bytes (64).
+pub fn sys_crypto_sign(scheme: usize, args: usize) -> SyscallResult {
+    if u16::try_from(scheme)
+        .ok()
+        .and_then(SignatureScheme::from_code)
+        != Some(SignatureScheme::Ed25519)
+    {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let args: CryptoSignArgs = read_args(args)?;
+    if args.key_len != 32 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&copy_in(args.key, args.key_len)?);
+    let message = copy_in(args.message, args.message_len)?;
+
+    let pair = KeyPair::from_seed(&seed);
+    ct_zero(&mut seed);
+    let pair = pair.map_err(|_| SyscallError::InvalidArgument)?;
+    let sig = pair
+        .sign(&message)
+        .map_err(|_| SyscallError::InvalidArgument)?;
+    if args.public_key != 0 {
+        copy_out(
+            args.public_key,
+            args.public_key_len,
+            pair.verifying_key.as_bytes(),
+        )?;
+    }
+    copy_out(args.signature, args.signature_len, sig.as_bytes())
+}
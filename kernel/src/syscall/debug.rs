@@ -8,13 +8,25 @@
 //! - PEEKTEXT/PEEKDATA: Read a word from tracee's address space via VAS
 //! - POKETEXT/POKEDATA: Write a word to tracee's address space via VAS
 //!
+//! Every request but TRACEME names another process: the caller must not be
+//! sandboxed, and must be uid 0, the target's parent or share its effective
+//! uid.
+//!
 //! Deferred (requires scheduler integration):
 //! - GETREGS/SETREGS: Read/write register state
 //! - ATTACH/DETACH: Tracer relationship management
 //! - CONT/SINGLESTEP: Resume control
+//!
+//! Also provides `trace_ctl` (82), the control interface to the kernel
+//! tracepoints in `perf::trace`.
 
 use super::{SyscallError, SyscallResult};
-use crate::{mm::VirtualAddress, process};
+use crate::{
+    mm::VirtualAddress,
+    perf::trace::{self, TraceEvent, TraceStats},
+    process,
+    syscall::uaccess::{copy_slice_to_user, copy_to_user},
+};
 
 // ============================================================================
 // Ptrace request codes (matching POSIX/Linux conventions)
@@ -183,6 +195,22 @@ fn phys_to_kernel_vaddr(phys_addr: usize) -> usize {
 // Syscall implementation
 // ============================================================================
 
+/// Check that `caller` may trace `target_pid`.
+fn check_tracer(
+    caller: &process::Process,
+    target_pid: process::ProcessId,
+) -> Result<(), SyscallError> {
+    crate::security::sandbox::check_ptrace(caller, target_pid.0)
+        .map_err(|_| SyscallError::AccessDenied)?;
+    let target = process::find_process(target_pid).ok_or(SyscallError::ProcessNotFound)?;
+    let euid = caller.euid();
+    if euid == 0 || target.parent == Some(caller.pid) || target.euid() == euid {
+        Ok(())
+    } else {
+        Err(SyscallError::PermissionDenied)
+    }
+}
+
 /// Process trace syscall (syscall 140).
 ///
 /// Provides debugger-level control over another process. The tracer must
@@ -199,7 +227,10 @@ fn phys_to_kernel_vaddr(phys_addr: usize) -> usize {
 /// Request-specific value on success, or error.
 pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> SyscallResult {
     let req = PtraceRequest::try_from(request).map_err(|_| SyscallError::InvalidArgument)?;
-    let _caller = process::current_process().ok_or(SyscallError::InvalidState)?;
+    let caller = process::current_process().ok_or(SyscallError::InvalidState)?;
+    if req != PtraceRequest::TraceMe {
+        check_tracer(caller, process::ProcessId(pid as u64))?;
+    }
 
     match req {
         PtraceRequest::TraceMe => {
@@ -272,3 +303,88 @@ pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> Sysca
         }
     }
 }
+
+// ============================================================================
+// trace_ctl: kernel tracepoint control
+// ============================================================================
+
+/// Operation selected by the first `trace_ctl` argument.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceCtlOp {
+    /// Start recording.
+    Enable = 0,
+    /// Stop recording (buffered events are kept).
+    Disable = 1,
+    /// Record only the event types set in the mask `arg1`.
+    SetMask = 2,
+    /// Record only events raised while process `arg1` runs (0 = all).
+    SetPid = 3,
+    /// Drop buffered events and zero the counters.
+    Clear = 4,
+    /// Copy up to `arg2` bytes of trace records to `arg1`.
+    Read = 5,
+    /// Copy the recording state and counters to `arg1` (`arg2` bytes).
+    Stats = 6,
+}
+
+impl TryFrom<usize> for TraceCtlOp {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TraceCtlOp::Enable),
+            1 => Ok(TraceCtlOp::Disable),
+            2 => Ok(TraceCtlOp::SetMask),
+            3 => Ok(TraceCtlOp::SetPid),
+            4 => Ok(TraceCtlOp::Clear),
+            5 => Ok(TraceCtlOp::Read),
+            6 => Ok(TraceCtlOp::Stats),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Control kernel tracing (root only).
+///
+/// `Read` returns the number of 32-byte records copied, oldest first, with
+/// timestamps converted to nanoseconds. The newest events are kept when the
+/// buffer is too small. Every other operation returns 0.
+pub fn sys_trace_ctl(op: usize, arg1: usize, arg2: usize) -> SyscallResult {
+    let op = TraceCtlOp::try_from(op).map_err(|_| SyscallError::InvalidArgument)?;
+    let caller = process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    match op {
+        TraceCtlOp::Enable => trace::enable(),
+        TraceCtlOp::Disable => trace::disable(),
+        TraceCtlOp::SetMask => {
+            if arg1 & !(trace::ALL_EVENTS as usize) != 0 {
+                return Err(SyscallError::InvalidArgument);
+            }
+            trace::set_event_mask(arg1 as u32);
+        }
+        TraceCtlOp::SetPid => trace::set_pid_filter(arg1 as u64),
+        TraceCtlOp::Clear => trace::clear(),
+        TraceCtlOp::Read => {
+            let max = arg2 / core::mem::size_of::<TraceEvent>();
+            let mut events = trace::collect(max);
+            for event in &mut events {
+                event.timestamp = crate::bench::cycles_to_ns(event.timestamp);
+            }
+            // TraceEvent is repr(C) plain data without implicit padding
+            // (`_pad` is written explicitly), so it can be copied out as is.
+            copy_slice_to_user(arg1, &events)?;
+            return Ok(events.len());
+        }
+        TraceCtlOp::Stats => {
+            if arg2 < core::mem::size_of::<TraceStats>() {
+                return Err(SyscallError::InvalidArgument);
+            }
+            copy_to_user(arg1, &trace::stats())?;
+        }
+    }
+    Ok(0)
+}
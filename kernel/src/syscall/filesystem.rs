@@ -11,145 +11,99 @@
 
 #![allow(clippy::unnecessary_cast)]
 
-#[allow(unused_imports)]
 use super::{
-    validate_user_buffer, validate_user_ptr_typed, validate_user_string_ptr, SyscallError,
-    SyscallResult,
+    uaccess::{
+        access_ok, copy_bytes_to_user, copy_from_user, copy_slice_from_user, copy_slice_to_user,
+        copy_to_user, strncpy_from_user, Access, PATH_MAX,
+    },
+    SyscallError, SyscallResult,
+};
+#[cfg(feature = "net")]
+use crate::net::{
+    socket::{SocketHandle, SocketNode},
+    zero_copy::{SendFile, SpliceEnd},
 };
 use crate::{
-    fs::{try_get_vfs, OpenFlags, Permissions, SeekFrom},
-    process,
+    error::{FsError, KernelError},
+    fs::{
+        acl::{Acl, ACL_ACCESS_XATTR, ACL_DEFAULT_XATTR},
+        try_get_vfs,
+        xattr::{XATTR_CREATE, XATTR_MAX_VALUE_SIZE, XATTR_REPLACE},
+        OpenFlags, Permissions, SeekFrom, VfsNode,
+    },
+    process::{
+        self,
+        creds::{Credentials, ACCESS_EXEC, ACCESS_READ, ACCESS_WRITE},
+    },
+    security::AccessType,
 };
 
 // ---------------------------------------------------------------------------
-// Architecture-specific serial I/O helpers for syscall fallback
+// Credential helpers
 // ---------------------------------------------------------------------------
 
-/// Write a single byte to the serial UART.
-///
-/// Used as a fallback when stdout/stderr file descriptors are not yet set up
-/// in the process's file table.
-fn serial_write_byte(byte: u8) {
-    #[cfg(target_arch = "x86_64")]
-    {
-        use core::fmt::Write;
-        // Use the kernel's initialized serial port (COM1 at 0x3F8).
-        // This goes through the uart_16550 driver with proper FIFO handling.
-        x86_64::instructions::interrupts::without_interrupts(|| {
-            crate::arch::x86_64::serial::SERIAL1
-                .lock()
-                .write_char(byte as char)
-                .ok();
-        });
-    }
-
-    #[cfg(target_arch = "aarch64")]
-    {
-        // Direct MMIO write to PL011 UART data register (QEMU virt machine).
-        const UART_DR: usize = 0x0900_0000;
-        // SAFETY: The PL011 UART data register at 0x09000000 is memory-mapped
-        // I/O on the QEMU virt machine. Writing a byte transmits a character.
-        // volatile_write ensures the compiler does not elide the store.
-        unsafe {
-            core::ptr::write_volatile(UART_DR as *mut u8, byte);
-        }
-    }
+/// Credentials of the calling process (root for kernel-internal callers).
+pub(crate) fn current_credentials() -> Credentials {
+    process::current_process()
+        .map(|p| p.credentials())
+        .unwrap_or_default()
+}
 
-    #[cfg(target_arch = "riscv64")]
-    {
-        // SBI legacy console putchar (function 0x01).
-        // SAFETY: The ecall instruction invokes the SBI console putchar
-        // interface. a0 holds the character, a7 holds the function ID.
-        // This is the standard mechanism for RISC-V console output.
-        unsafe {
-            core::arch::asm!(
-                "ecall",
-                in("a0") byte as usize,
-                in("a7") 0x01usize,
-                options(nostack, nomem)
-            );
-        }
+/// Require `mask` access to `node` for `creds`.
+fn require_access(node: &dyn VfsNode, creds: &Credentials, mask: u32) -> Result<(), SyscallError> {
+    crate::fs::check_access(node, creds, mask).map_err(super::map_kernel_error)
+}
+
+/// Require write and search permission on the directory containing `path`,
+/// as needed to create or remove an entry in it.
+fn require_parent_writable(path: &str, creds: &Credentials) -> Result<(), SyscallError> {
+    if creds.is_privileged() {
+        return Ok(());
     }
+    let (parent_path, _) = split_path(path)?;
+    let vfs_guard = vfs()?.read();
+    let parent = vfs_guard
+        .resolve_path(&parent_path)
+        .map_err(map_resolve_err)?;
+    require_access(&*parent, creds, ACCESS_WRITE | ACCESS_EXEC)
 }
 
-/// Try to read a single byte from the serial UART (non-blocking).
-///
-/// Returns `Some(byte)` if data is available, `None` otherwise.
-/// Used as a fallback when the stdin file descriptor is not yet set up.
-fn serial_try_read_byte() -> Option<u8> {
-    #[cfg(target_arch = "x86_64")]
-    {
-        // Check Line Status Register (base + 5) bit 0 for data ready,
-        // then read from data register (base + 0) at COM1 (0x3F8).
-        let status: u8;
-        // SAFETY: Reading the Line Status Register at I/O port 0x3FD.
-        // This is a well-defined 16550 UART register read.
-        unsafe {
-            core::arch::asm!(
-                "in al, dx",
-                out("al") status,
-                in("dx") 0x3FDu16,
-                options(nomem, nostack)
-            );
-        }
-        if (status & 1) != 0 {
-            let data: u8;
-            // SAFETY: Reading the data register at I/O port 0x3F8.
-            // The LSR check above confirmed data is available.
-            unsafe {
-                core::arch::asm!(
-                    "in al, dx",
-                    out("al") data,
-                    in("dx") 0x3F8u16,
-                    options(nomem, nostack)
-                );
-            }
-            Some(data)
-        } else {
-            None
-        }
+/// Require the caller's sandbox profile, if it has one, to allow `access`
+/// to `path` (relative to `dirfd` or the working directory). With
+/// `follow_last` unset a final symlink is checked rather than its target.
+pub(crate) fn require_sandbox(
+    dirfd: usize,
+    path: &str,
+    access: AccessType,
+    follow_last: bool,
+) -> Result<(), SyscallError> {
+    let Some(process) = process::current_process() else {
+        return Ok(());
+    };
+    if process.sandbox.lock().is_none() {
+        return Ok(());
     }
+    let abs_path = resolve_at_path(dirfd, path)?;
+    crate::security::sandbox::check_path(process, &abs_path, access, follow_last)
+        .map_err(|_| SyscallError::AccessDenied)
+}
 
-    #[cfg(target_arch = "aarch64")]
-    {
-        const UART_BASE: usize = 0x0900_0000;
-        const UART_FR: usize = UART_BASE + 0x18; // Flag register
-        const UART_DR: usize = UART_BASE; // Data register
-
-        // SAFETY: Reading PL011 UART MMIO registers. The QEMU virt machine
-        // maps the UART at this address. volatile_read prevents reordering.
-        unsafe {
-            let flags = core::ptr::read_volatile(UART_FR as *const u32);
-            if (flags & (1 << 4)) == 0 {
-                // RXFE bit clear = data available
-                let data = core::ptr::read_volatile(UART_DR as *const u32);
-                Some((data & 0xFF) as u8)
-            } else {
-                None
-            }
-        }
+/// Sandbox access an open with `flags` needs.
+fn open_access(flags: &OpenFlags) -> AccessType {
+    if flags.write || flags.append || flags.truncate || flags.create {
+        AccessType::Write
+    } else {
+        AccessType::Read
     }
+}
 
-    #[cfg(target_arch = "riscv64")]
-    {
-        // SBI legacy console getchar (function 0x02).
-        let result: isize;
-        // SAFETY: The ecall invokes SBI console_getchar. Returns the
-        // character in a0, or -1 if no data is available.
-        unsafe {
-            core::arch::asm!(
-                "li a7, 0x02",
-                "ecall",
-                out("a0") result,
-                out("a7") _,
-                options(nomem)
-            );
-        }
-        if result >= 0 {
-            Some(result as u8)
-        } else {
-            None
-        }
+/// Give a newly created node the creator's effective uid/gid.
+///
+/// Filesystems create nodes owned by root; filesystems without ownership
+/// support simply keep that.
+fn set_creator_owner(node: &dyn VfsNode, creds: &Credentials) {
+    if creds.euid != 0 || creds.egid != 0 {
+        let _ = node.chown(creds.euid, creds.egid);
     }
 }
 
@@ -157,9 +111,20 @@ fn serial_try_read_byte() -> Option<u8> {
 /// Prevents unbounded kernel-side loops for large writes.
 const SERIAL_IO_MAX_SIZE: usize = 64 * 1024;
 
+/// Largest transfer a single read/write moves through its kernel bounce
+/// buffer (1 MB). Larger requests complete short, which POSIX permits.
+const IO_BOUNCE_MAX: usize = 1024 * 1024;
+
+/// Copy the first `len` bytes of a read's bounce buffer out to user space
+/// and return `len`.
+fn finish_read(buffer: usize, data: &[u8], len: usize) -> SyscallResult {
+    copy_bytes_to_user(buffer, &data[..len])?;
+    Ok(len)
+}
+
 /// Helper to get the VFS instance, returning a syscall error instead of
 /// panicking if the VFS subsystem has not been initialized yet.
-pub(crate) fn vfs() -> Result<&'static spin::RwLock<crate::fs::Vfs>, SyscallError> {
+pub(crate) fn vfs() -> Result<&'static crate::sync::lockdep::RwLock<crate::fs::Vfs>, SyscallError> {
     try_get_vfs().ok_or(SyscallError::InvalidState)
 }
 
@@ -178,71 +143,44 @@ use alloc::vec::Vec;
 /// # Returns
 /// File descriptor on success
 pub fn sys_open(path: usize, flags: usize, mode: usize) -> SyscallResult {
-    // Validate path pointer is in user space
-    validate_user_string_ptr(path)?;
-
-    // Get path string from user space
-    // SAFETY: path was validated as non-null and in user-space above. We read
-    // time from the user-space pointer until we find a null terminator or
-    // reach the 4096-byte limit. The caller must provide a valid, null-
-    // terminated string in mapped user memory.
-    let path_bytes = unsafe {
-        let mut bytes = Vec::new();
-        let mut ptr = path as *const u8;
-
-        // Read until null terminator (max 4096 bytes)
-        for _ in 0..4096 {
-            let byte = *ptr;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-            ptr = ptr.add(1);
-        }
-        bytes
-    };
-
-    let path_str = match core::str::from_utf8(&path_bytes) {
-        Ok(s) => s,
-        Err(_) => return Err(SyscallError::InvalidArgument),
-    };
+    let path_buf = read_user_path(path)?;
+    let path_str = path_buf.as_str();
 
     // Get current process
     let process = process::current_process().ok_or(SyscallError::InvalidState)?;
+    let creds = process.credentials();
 
     // Convert flags. O_CLOEXEC (0x80000) is handled separately.
     let open_flags = OpenFlags::from_bits(flags as u32).ok_or(SyscallError::InvalidArgument)?;
     let cloexec = (flags & 0x80000) != 0; // O_CLOEXEC
 
+    // /dev/fd/N opens another reference to the caller's descriptor N (used
+    // by shell process substitution)
+    if let Some(fd) = path_str.strip_prefix("/dev/fd/") {
+        let fd: usize = fd.parse().map_err(|_| SyscallError::ResourceNotFound)?;
+        let file_table = process.file_table.lock();
+        let result = if cloexec {
+            file_table.dup_cloexec(fd)
+        } else {
+            file_table.dup(fd)
+        };
+        return result.map_err(|_| SyscallError::BadFileDescriptor);
+    }
+
+    require_sandbox(AT_FDCWD, path_str, open_access(&open_flags), true)?;
+
     // Open the file through VFS
     match vfs()?.read().open(path_str, open_flags) {
         Ok(node) => {
-            // Permission check: verify the caller has the required access.
-            // Root (uid 0) bypasses all permission checks.
-            if process.uid != 0 {
-                if let Ok(meta) = node.metadata() {
-                    let p = &meta.permissions;
-                    let is_owner = process.uid == meta.uid;
-                    let is_group = process.gid == meta.gid;
-
-                    if open_flags.read {
-                        let ok = (is_owner && p.owner_read)
-                            || (is_group && p.group_read)
-                            || p.other_read;
-                        if !ok {
-                            return Err(SyscallError::PermissionDenied);
-                        }
-                    }
-                    if open_flags.write || open_flags.append || open_flags.truncate {
-                        let ok = (is_owner && p.owner_write)
-                            || (is_group && p.group_write)
-                            || p.other_write;
-                        if !ok {
-                            return Err(SyscallError::PermissionDenied);
-                        }
-                    }
-                }
+            // Permission check against the caller's effective credentials
+            let mut mask = 0;
+            if open_flags.read {
+                mask |= ACCESS_READ;
+            }
+            if open_flags.write || open_flags.append || open_flags.truncate {
+                mask |= ACCESS_WRITE;
             }
+            require_access(&*node, &creds, mask)?;
 
             // Handle O_TRUNC on existing files
             if open_flags.truncate {
@@ -275,8 +213,10 @@ pub fn sys_open(path: usize, flags: usize, mode: usize) -> SyscallResult {
                         return Err(SyscallError::ResourceNotFound);
                     }
                 };
+                require_access(&*parent, &creds, ACCESS_WRITE | ACCESS_EXEC)?;
                 match parent.create(&name, perms) {
                     Ok(node) => {
+                        set_creator_owner(&*node, &creds);
                         let file = crate::fs::file::File::new_with_path(
                             node,
                             open_flags,
@@ -324,15 +264,17 @@ pub fn sys_close(fd: usize) -> SyscallResult {
 /// Number of bytes actually read
 ///
 /// For fd 0 (stdin), if the process does not have a file descriptor table
-/// entry, falls back to polling-mode serial UART input. This allows the
+/// entry, falls back to reading the serial console. This allows the
 /// embedded shell to accept keyboard input before a full console subsystem
 /// is initialized.
 pub fn sys_read(fd: usize, buffer: usize, count: usize) -> SyscallResult {
     if count == 0 {
         return Ok(0);
     }
-    // Validate buffer is in user space
-    validate_user_buffer(buffer, count)?;
+    // Read into a kernel buffer; only what arrived is copied out
+    let count = count.min(IO_BOUNCE_MAX);
+    access_ok(buffer, count, Access::Write)?;
+    let mut buffer_slice = alloc::vec![0u8; count];
 
     // For stdin (fd 0), try file table first, then fall back to serial
     if fd == 0 {
@@ -340,13 +282,8 @@ pub fn sys_read(fd: usize, buffer: usize, count: usize) -> SyscallResult {
         if let Some(proc) = process::current_process() {
             let file_table = proc.file_table.lock();
             if let Some(file_desc) = file_table.get(fd) {
-                // SAFETY: buffer is non-zero (checked above). The caller
-                // must provide a valid, writable buffer of at least `count`
-                // bytes. from_raw_parts_mut creates a mutable slice.
-                let buffer_slice =
-                    unsafe { core::slice::from_raw_parts_mut(buffer as *mut u8, count) };
-                return match file_desc.read(buffer_slice) {
-                    Ok(bytes_read) => Ok(bytes_read),
+                return match file_desc.read(&mut buffer_slice) {
+                    Ok(bytes_read) => finish_read(buffer, &buffer_slice, bytes_read),
                     Err(crate::error::KernelError::WouldBlock) => Err(SyscallError::WouldBlock),
                     Err(crate::error::KernelError::BrokenPipe) => Ok(0),
                     Err(_) => Err(SyscallError::InvalidState),
@@ -354,49 +291,12 @@ pub fn sys_read(fd: usize, buffer: usize, count: usize) -> SyscallResult {
             }
         }
 
-        // Fallback: read from serial UART, respecting terminal state.
+        // Fallback: read from the serial console.
         let read_count = count.min(SERIAL_IO_MAX_SIZE);
-        // SAFETY: buffer is non-zero (checked above). We limit the size
-        // via SERIAL_IO_MAX_SIZE. The caller must provide a valid writable
-        // buffer of at least `count` bytes. During early bring-up this
-        // may be a kernel-space address from the embedded init binary.
-        let buffer_slice =
-            unsafe { core::slice::from_raw_parts_mut(buffer as *mut u8, read_count) };
-
-        let canonical = crate::drivers::terminal::is_canonical_mode();
-        let echo = crate::drivers::terminal::is_echo_enabled();
-
-        let mut bytes_read = 0;
-        for slot in buffer_slice.iter_mut() {
-            // Spin-wait for a byte to become available
-            let byte = loop {
-                if let Some(b) = serial_try_read_byte() {
-                    break b;
-                }
-                core::hint::spin_loop();
-            };
-
-            // Echo if enabled
-            if echo {
-                serial_write_byte(byte);
-            }
-
-            *slot = byte;
-            bytes_read += 1;
 
-            // In canonical mode, stop after newline or carriage return.
-            // In raw mode, return immediately after each character (VMIN=1).
-            if canonical {
-                if byte == b'\n' || byte == b'\r' {
-                    break;
-                }
-            } else {
-                // Raw mode: return after first character
-                break;
-            }
-        }
-
-        return Ok(bytes_read);
+        // The console line discipline applies the terminal state.
+        let bytes_read = crate::drivers::serial::read_console(&mut buffer_slice[..read_count]);
+        return finish_read(buffer, &buffer_slice, bytes_read);
     }
 
     // Non-stdin: use file table normally.
@@ -409,18 +309,20 @@ pub fn sys_read(fd: usize, buffer: usize, count: usize) -> SyscallResult {
     // parent reads from a pipe that the child writes to.
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
 
-    // SAFETY: buffer was validated as non-zero above. The caller must
-    // provide a valid, writable user-space buffer of at least `count`
-    // bytes. from_raw_parts_mut creates a mutable slice for the read.
-    let buffer_slice = unsafe { core::slice::from_raw_parts_mut(buffer as *mut u8, count) };
-
     // First attempt: try reading directly.
     {
         let file_table = proc.file_table.lock();
         let file_desc = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
-        match file_desc.read(buffer_slice) {
-            Ok(bytes_read) => return Ok(bytes_read),
+        match file_desc.read(&mut buffer_slice) {
+            Ok(bytes_read) => return finish_read(buffer, &buffer_slice, bytes_read),
             Err(crate::error::KernelError::WouldBlock) => {
+                // Sockets wait (or not) according to O_NONBLOCK and
+                // SO_RCVTIMEO, like recv().
+                #[cfg(feature = "net")]
+                if is_socket(&file_desc) {
+                    drop(file_table);
+                    return super::sys_socket_recv(fd, buffer, count, 0);
+                }
                 // Pipe empty with write end open -- fall through to try
                 // dispatching children in boot context.
             }
@@ -469,8 +371,8 @@ pub fn sys_read(fd: usize, buffer: usize, count: usize) -> SyscallResult {
                 // Retry the read after dispatching the child.
                 let file_table = proc.file_table.lock();
                 if let Some(file_desc) = file_table.get(fd) {
-                    match file_desc.read(buffer_slice) {
-                        Ok(bytes_read) => return Ok(bytes_read),
+                    match file_desc.read(&mut buffer_slice) {
+                        Ok(bytes_read) => return finish_read(buffer, &buffer_slice, bytes_read),
                         Err(crate::error::KernelError::WouldBlock) => {
                             // Still empty -- continue dispatching if we ran a child
                             drop(file_table);
@@ -513,8 +415,9 @@ pub fn sys_write(fd: usize, buffer: usize, count: usize) -> SyscallResult {
     if count == 0 {
         return Ok(0);
     }
-    // Validate buffer is in user space
-    validate_user_buffer(buffer, count)?;
+    // Copy the data in before handing it to the file
+    let count = count.min(IO_BOUNCE_MAX);
+    let buffer_slice = copy_slice_from_user::<u8>(buffer, count)?;
 
     // For stdout (fd 1) and stderr (fd 2), try file table first, then
     // fall back to serial output
@@ -523,12 +426,7 @@ pub fn sys_write(fd: usize, buffer: usize, count: usize) -> SyscallResult {
         if let Some(proc) = process::current_process() {
             let file_table = proc.file_table.lock();
             if let Some(file_desc) = file_table.get(fd) {
-                // SAFETY: buffer is non-zero (checked above). The caller
-                // must provide a valid, readable buffer of at least `count`
-                // bytes. from_raw_parts creates an immutable slice.
-                let buffer_slice =
-                    unsafe { core::slice::from_raw_parts(buffer as *const u8, count) };
-                return match file_desc.write(buffer_slice) {
+                return match file_desc.write(&buffer_slice) {
                     Ok(bytes_written) => Ok(bytes_written),
                     Err(crate::error::KernelError::BrokenPipe) => Err(SyscallError::BrokenPipe),
                     Err(crate::error::KernelError::WouldBlock) => Err(SyscallError::WouldBlock),
@@ -537,17 +435,10 @@ pub fn sys_write(fd: usize, buffer: usize, count: usize) -> SyscallResult {
             }
         }
 
-        // Fallback: write directly to serial UART
+        // Fallback: write to the serial console
         let write_count = count.min(SERIAL_IO_MAX_SIZE);
-        // SAFETY: buffer is non-zero (checked above). We limit the size
-        // via SERIAL_IO_MAX_SIZE. The caller must provide a valid readable
-        // buffer of at least `count` bytes. During early bring-up this
-        // may be a kernel-space address from the embedded init binary.
-        let buffer_slice = unsafe { core::slice::from_raw_parts(buffer as *const u8, write_count) };
-
-        for &byte in buffer_slice {
-            serial_write_byte(byte);
-        }
+
+        crate::drivers::serial::write_console(&buffer_slice[..write_count]);
 
         return Ok(write_count);
     }
@@ -557,25 +448,31 @@ pub fn sys_write(fd: usize, buffer: usize, count: usize) -> SyscallResult {
     let file_table = proc.file_table.lock();
     let file_desc = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
 
-    // SAFETY: buffer was validated as non-zero above. The caller must
-    // provide a valid, readable user-space buffer of at least `count`
-    // bytes. from_raw_parts creates an immutable slice for the write.
-    let buffer_slice = unsafe { core::slice::from_raw_parts(buffer as *const u8, count) };
-
-    match file_desc.write(buffer_slice) {
+    match file_desc.write(&buffer_slice) {
         Ok(bytes_written) => Ok(bytes_written),
         Err(crate::error::KernelError::BrokenPipe) => Err(SyscallError::BrokenPipe),
+        #[cfg(feature = "net")]
+        Err(crate::error::KernelError::WouldBlock) if is_socket(&file_desc) => {
+            drop(file_table);
+            super::sys_socket_send(fd, buffer, count, 0)
+        }
         Err(crate::error::KernelError::WouldBlock) => Err(SyscallError::WouldBlock),
         Err(_) => Err(SyscallError::InvalidState),
     }
 }
 
+/// Whether `file` is a socket descriptor.
+#[cfg(feature = "net")]
+fn is_socket(file: &crate::fs::file::File) -> bool {
+    file.node.as_any().is_some_and(|any| any.is::<SocketNode>())
+}
+
 /// Seek within a file
 ///
 /// # Arguments
 /// - fd: File descriptor
 /// - offset: Offset to seek
-/// - whence: Seek origin (0=start, 1=current, 2=end)
+/// - whence: Seek origin (0=start, 1=current, 2=end, 3=next data, 4=next hole)
 ///
 /// # Returns
 /// New file position
@@ -587,11 +484,24 @@ pub fn sys_seek(fd: usize, offset: isize, whence: usize) -> SyscallResult {
     let file_table = process.file_table.lock();
     let file_desc = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
 
-    // Convert whence to SeekFrom
+    // Convert whence to SeekFrom. SEEK_DATA (3) and SEEK_HOLE (4) ask the
+    // node for the next data/hole offset; ENXIO if there is none.
     let seek_from = match whence {
         0 => SeekFrom::Start(offset as usize),
         1 => SeekFrom::Current(offset),
         2 => SeekFrom::End(offset),
+        3 | 4 => {
+            let start = usize::try_from(offset).map_err(|_| SyscallError::NoSuchAddress)?;
+            let found = if whence == 3 {
+                file_desc.node.seek_data(start)
+            } else {
+                file_desc.node.seek_hole(start)
+            };
+            let pos = found
+                .map_err(super::map_kernel_error)?
+                .ok_or(SyscallError::NoSuchAddress)?;
+            SeekFrom::Start(pos)
+        }
         _ => return Err(SyscallError::InvalidArgument),
     };
 
@@ -609,8 +519,6 @@ pub fn sys_seek(fd: usize, offset: isize, whence: usize) -> SyscallResult {
 /// - stat_buf: Buffer to write stat structure
 pub fn sys_stat(fd: usize, stat_buf: usize) -> SyscallResult {
     // Validate stat buffer pointer is in user space and aligned for FileStat
-    validate_user_ptr_typed::<FileStat>(stat_buf)?;
-
     // Get current process
     let process = process::current_process().ok_or(SyscallError::InvalidState)?;
 
@@ -623,12 +531,9 @@ pub fn sys_stat(fd: usize, stat_buf: usize) -> SyscallResult {
         .node
         .metadata()
         .map_err(|_| SyscallError::InvalidState)?;
-    let stat = fill_stat(&metadata);
+    let stat = fill_stat(&metadata, file_desc.node.link_count());
 
-    // SAFETY: stat_buf was validated as non-null, in user-space, and aligned.
-    unsafe {
-        core::ptr::write(stat_buf as *mut FileStat, stat);
-    }
+    copy_to_user(stat_buf, &stat)?;
     Ok(0)
 }
 
@@ -652,43 +557,64 @@ pub fn sys_truncate(fd: usize, size: usize) -> SyscallResult {
     }
 }
 
+/// Allocate or deallocate file space (syscall 380).
+///
+/// Linux ABI: `fallocate(fd, mode, offset, len)`. `mode` is 0 or a
+/// combination of `FALLOC_FL_KEEP_SIZE` and `FALLOC_FL_PUNCH_HOLE`; punching
+/// a hole requires `FALLOC_FL_KEEP_SIZE`. Filesystems without support fail
+/// with `EOPNOTSUPP`.
+pub fn sys_fallocate(fd: usize, mode: usize, offset: usize, len: usize) -> SyscallResult {
+    use crate::fs::{FALLOC_FL_KEEP_SIZE, FALLOC_FL_PUNCH_HOLE};
+
+    let mode = mode as u32;
+    if (offset as isize) < 0 || len as isize <= 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    if mode & !(FALLOC_FL_KEEP_SIZE | FALLOC_FL_PUNCH_HOLE) != 0 {
+        return Err(SyscallError::OperationNotSupported);
+    }
+    if mode & FALLOC_FL_PUNCH_HOLE != 0 && mode & FALLOC_FL_KEEP_SIZE == 0 {
+        return Err(SyscallError::OperationNotSupported);
+    }
+
+    let process = process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = process.file_table.lock();
+    let file_desc = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
+    if !file_desc.flags.write {
+        return Err(SyscallError::BadFileDescriptor);
+    }
+
+    match file_desc.node.fallocate(mode, offset, len) {
+        Ok(()) => Ok(0),
+        Err(KernelError::NotImplemented { .. }) => Err(SyscallError::OperationNotSupported),
+        Err(KernelError::FsError(FsError::FileTooLarge)) => Err(SyscallError::FileTooLarge),
+        Err(e) => Err(super::map_kernel_error(e)),
+    }
+}
+
 /// Create a directory
 ///
 /// # Arguments
 /// - path: Path to new directory
 /// - mode: Directory permissions
 pub fn sys_mkdir(path: usize, mode: usize) -> SyscallResult {
-    // Validate path pointer is in user space
-    validate_user_string_ptr(path)?;
-
-    // Get path string
-    // SAFETY: path was validated as non-null and in user-space above. We read
-    // user-space pointer until null terminator or 4096-byte limit. The
-    // caller must provide a valid null-terminated string.
-    let path_bytes = unsafe {
-        let mut bytes = Vec::new();
-        let mut ptr = path as *const u8;
-
-        for _ in 0..4096 {
-            let byte = *ptr;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-            ptr = ptr.add(1);
-        }
-        bytes
-    };
+    let path_buf = read_user_path(path)?;
+    let path_str = path_buf.as_str();
 
-    let path_str = match core::str::from_utf8(&path_bytes) {
-        Ok(s) => s,
-        Err(_) => return Err(SyscallError::InvalidArgument),
-    };
+    require_sandbox(AT_FDCWD, path_str, AccessType::Write, false)?;
+    let creds = current_credentials();
+    require_parent_writable(path_str, &creds)?;
 
     // Create directory through VFS
     let permissions = Permissions::from_mode(mode as u32);
-    match vfs()?.read().mkdir(path_str, permissions) {
-        Ok(_) => Ok(0),
+    let vfs_guard = vfs()?.read();
+    match vfs_guard.mkdir(path_str, permissions) {
+        Ok(_) => {
+            if let Ok(node) = vfs_guard.resolve_path(path_str) {
+                set_creator_owner(&*node, &creds);
+            }
+            Ok(0)
+        }
         Err(e) => Err(super::map_kernel_error(e)),
     }
 }
@@ -698,32 +624,11 @@ pub fn sys_mkdir(path: usize, mode: usize) -> SyscallResult {
 /// # Arguments
 /// - path: Path to directory to remove
 pub fn sys_rmdir(path: usize) -> SyscallResult {
-    // Validate path pointer is in user space
-    validate_user_string_ptr(path)?;
-
-    // Get path string
-    // SAFETY: path was validated as non-null and in user-space above. We read
-    // bytes from the user-space pointer until null terminator or 4096-byte
-    // limit. The caller must provide a valid null-terminated string.
-    let path_bytes = unsafe {
-        let mut bytes = Vec::new();
-        let mut ptr = path as *const u8;
-
-        for _ in 0..4096 {
-            let byte = *ptr;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-            ptr = ptr.add(1);
-        }
-        bytes
-    };
+    let path_buf = read_user_path(path)?;
+    let path_str = path_buf.as_str();
 
-    let path_str = match core::str::from_utf8(&path_bytes) {
-        Ok(s) => s,
-        Err(_) => return Err(SyscallError::InvalidArgument),
-    };
+    require_sandbox(AT_FDCWD, path_str, AccessType::Write, false)?;
+    require_parent_writable(path_str, &current_credentials())?;
 
     // Remove directory through VFS
     match vfs()?.read().unlink(path_str) {
@@ -741,16 +646,7 @@ pub fn sys_rmdir(path: usize) -> SyscallResult {
 /// - flags: Mount flags
 ///
 /// This is a privileged operation requiring a kernel-level capability.
-pub fn sys_mount(
-    _device: usize,
-    mount_point: usize,
-    fs_type: usize,
-    flags: usize,
-) -> SyscallResult {
-    // Validate mount_point and fs_type string pointers are in user space
-    validate_user_string_ptr(mount_point)?;
-    validate_user_string_ptr(fs_type)?;
-
+pub fn sys_mount(device: usize, mount_point: usize, fs_type: usize, flags: usize) -> SyscallResult {
     // Mount is a privileged operation - verify the calling process has
     // a Memory capability with WRITE rights (needed to modify the VFS tree)
     let current = process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -777,56 +673,34 @@ pub fn sys_mount(
     }
 
     // Get mount point path
-    // SAFETY: mount_point was validated as non-zero above. We read bytes
-    // from the user-space pointer until null terminator or 4096-byte limit.
-    let mount_path_bytes = unsafe {
-        let mut bytes = Vec::new();
-        let mut ptr = mount_point as *const u8;
-
-        for _ in 0..4096 {
-            let byte = *ptr;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-            ptr = ptr.add(1);
-        }
-        bytes
-    };
-
-    let mount_path = match core::str::from_utf8(&mount_path_bytes) {
-        Ok(s) => s,
-        Err(_) => return Err(SyscallError::InvalidArgument),
-    };
+    let mount_path_buf = read_user_path(mount_point)?;
+    let mount_path = mount_path_buf.as_str();
+    require_sandbox(AT_FDCWD, mount_path, AccessType::Write, true)?;
 
     // Get filesystem type
-    // SAFETY: fs_type was validated as non-zero above. We read bytes from
-    // the user-space pointer until null terminator or 256-byte limit.
-    let fs_type_bytes = unsafe {
-        let mut bytes = Vec::new();
-        let mut ptr = fs_type as *const u8;
-
-        for _ in 0..256 {
-            let byte = *ptr;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-            ptr = ptr.add(1);
-        }
-        bytes
-    };
+    let fs_type_buf = strncpy_from_user(fs_type, 256)?;
+    let fs_type_str = fs_type_buf.as_str();
 
-    let fs_type_str = match core::str::from_utf8(&fs_type_bytes) {
-        Ok(s) => s,
-        Err(_) => return Err(SyscallError::InvalidArgument),
+    // Get the device: a registered block device holds the filesystem,
+    // anything else (or none) names a virtual filesystem
+    let device_buf = if device != 0 {
+        strncpy_from_user(device, 256)?
+    } else {
+        alloc::string::String::new()
     };
+    let device_str = device_buf.as_str();
 
     // Mount filesystem
-    match vfs()?
-        .write()
-        .mount_by_type(mount_path, fs_type_str, flags as u32)
-    {
+    let mut vfs = vfs()?.write();
+    let result = if crate::drivers::block::get(device_str).is_some() {
+        vfs.mount_block_device(mount_path, fs_type_str, device_str)
+    } else if fs_type_str == "9p" {
+        // The source of a 9p mount is the mount tag of the export
+        vfs.mount_9p(mount_path, device_str)
+    } else {
+        vfs.mount_by_type(mount_path, fs_type_str, flags as u32)
+    };
+    match result {
         Ok(_) => Ok(0),
         Err(_) => Err(SyscallError::InvalidState),
     }
@@ -839,9 +713,6 @@ pub fn sys_mount(
 ///
 /// This is a privileged operation requiring a kernel-level capability.
 pub fn sys_unmount(mount_point: usize) -> SyscallResult {
-    // Validate mount_point string pointer is in user space
-    validate_user_string_ptr(mount_point)?;
-
     // Unmount is a privileged operation - verify the calling process has
     // a Memory capability with WRITE rights (needed to modify the VFS tree)
     let current = process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -868,30 +739,12 @@ pub fn sys_unmount(mount_point: usize) -> SyscallResult {
     }
 
     // Get mount point path
-    // SAFETY: mount_point was validated as non-zero above. We read bytes
-    // from the user-space pointer until null terminator or 4096-byte limit.
-    let mount_path_bytes = unsafe {
-        let mut bytes = Vec::new();
-        let mut ptr = mount_point as *const u8;
-
-        for _ in 0..4096 {
-            let byte = *ptr;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-            ptr = ptr.add(1);
-        }
-        bytes
-    };
-
-    let mount_path = match core::str::from_utf8(&mount_path_bytes) {
-        Ok(s) => s,
-        Err(_) => return Err(SyscallError::InvalidArgument),
-    };
+    let mount_path_buf = read_user_path(mount_point)?;
+    let mount_path = mount_path_buf.as_str();
+    require_sandbox(AT_FDCWD, mount_path, AccessType::Write, true)?;
 
     // Unmount filesystem
-    match vfs()?.write().unmount(mount_path) {
+    match vfs()?.read().unmount(mount_path) {
         Ok(_) => Ok(0),
         Err(_) => Err(SyscallError::InvalidState),
     }
@@ -939,6 +792,7 @@ pub fn sys_fsync(fd: usize) -> SyscallResult {
 /// Key difference from naive layout: nlink comes before mode, and there
 /// is a 4-byte pad after gid, plus nanosecond fields and trailing padding.
 #[repr(C)]
+#[derive(Clone, Copy)]
 struct FileStat {
     st_dev: u64,        // offset 0
     st_ino: u64,        // offset 8
@@ -964,7 +818,7 @@ struct FileStat {
 const _: () = assert!(core::mem::size_of::<FileStat>() == 144);
 
 /// Helper: populate a FileStat from VFS metadata.
-fn fill_stat(metadata: &crate::fs::Metadata) -> FileStat {
+fn fill_stat(metadata: &crate::fs::Metadata, nlink: u32) -> FileStat {
     let mode = match metadata.node_type {
         crate::fs::NodeType::File => 0o100644,
         crate::fs::NodeType::Directory => 0o040755,
@@ -978,7 +832,7 @@ fn fill_stat(metadata: &crate::fs::Metadata) -> FileStat {
     FileStat {
         st_dev: 1,
         st_ino: metadata.inode,
-        st_nlink: 1,
+        st_nlink: nlink as u64,
         st_mode: mode,
         st_uid: metadata.uid,
         st_gid: metadata.gid,
@@ -1032,10 +886,7 @@ pub(crate) fn map_resolve_err(e: crate::error::KernelError) -> SyscallError {
 pub fn sys_dup(fd: usize) -> SyscallResult {
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
-    match file_table.dup(fd) {
-        Ok(new_fd) => Ok(new_fd),
-        Err(_) => Err(SyscallError::InvalidArgument),
-    }
+    file_table.dup(fd).map_err(super::map_kernel_error)
 }
 
 /// Duplicate a file descriptor to a specific number
@@ -1049,10 +900,10 @@ pub fn sys_dup(fd: usize) -> SyscallResult {
 pub fn sys_dup2(old_fd: usize, new_fd: usize) -> SyscallResult {
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
-    match file_table.dup2(old_fd, new_fd) {
-        Ok(()) => Ok(new_fd),
-        Err(_) => Err(SyscallError::InvalidArgument),
-    }
+    file_table
+        .dup2(old_fd, new_fd)
+        .map_err(super::map_kernel_error)?;
+    Ok(new_fd)
 }
 
 /// Create a pipe
@@ -1081,8 +932,6 @@ pub fn sys_getcwd(buf: usize, size: usize) -> SyscallResult {
     if size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(buf, size)?;
-
     let cwd = {
         let thread = process::current_thread().ok_or(SyscallError::InvalidState)?;
         #[cfg(feature = "alloc")]
@@ -1100,13 +949,8 @@ pub fn sys_getcwd(buf: usize, size: usize) -> SyscallResult {
         return Err(SyscallError::InvalidArgument); // Buffer too small
     }
 
-    // SAFETY: buf was validated above as non-null and in user space with
-    // sufficient size. We write cwd_bytes.len() + 1 bytes (including NUL).
-    unsafe {
-        let dst = buf as *mut u8;
-        core::ptr::copy_nonoverlapping(cwd_bytes.as_ptr(), dst, cwd_bytes.len());
-        *dst.add(cwd_bytes.len()) = 0; // NUL terminator
-    }
+    copy_bytes_to_user(buf, cwd_bytes)?;
+    copy_to_user(buf + cwd_bytes.len(), &0u8)?; // NUL terminator
 
     Ok(cwd_bytes.len())
 }
@@ -1119,8 +963,6 @@ pub fn sys_getcwd(buf: usize, size: usize) -> SyscallResult {
 /// # Returns
 /// 0 on success
 pub fn sys_chdir(path_ptr: usize) -> SyscallResult {
-    validate_user_string_ptr(path_ptr)?;
-
     let path = read_user_path(path_ptr)?;
     let thread = process::current_thread().ok_or(SyscallError::InvalidState)?;
     #[cfg(not(feature = "alloc"))]
@@ -1131,6 +973,8 @@ pub fn sys_chdir(path_ptr: usize) -> SyscallResult {
     #[cfg(feature = "alloc")]
     {
         let cwd = thread.fs().cwd.lock().clone();
+        let abs_path = crate::process::cwd::resolve_path(&path, &cwd);
+        require_sandbox(AT_FDCWD, &abs_path, AccessType::Read, true)?;
         let vfs_lock = vfs()?;
         let vfs_guard = vfs_lock.read();
         let node = vfs_guard
@@ -1146,6 +990,37 @@ pub fn sys_chdir(path_ptr: usize) -> SyscallResult {
     }
 }
 
+/// Terminal ioctls on a serial port other than the console.
+///
+/// Returns `None` for requests the port does not handle itself.
+fn serial_ioctl(
+    tty: &crate::drivers::serial::SerialTty,
+    cmd: usize,
+    arg: usize,
+) -> Option<SyscallResult> {
+    use crate::drivers::terminal::{
+        KernelTermios, KernelWinsize, TCGETS, TCSETS, TCSETSF, TCSETSW, TIOCGWINSZ,
+    };
+
+    if !matches!(cmd, TCGETS | TCSETS | TCSETSW | TCSETSF | TIOCGWINSZ) {
+        return None;
+    }
+    if arg == 0 {
+        return Some(Err(SyscallError::InvalidPointer));
+    }
+    Some(match cmd {
+        TCGETS => copy_to_user(arg, &tty.termios()).map(|()| 0),
+        TIOCGWINSZ => copy_to_user(arg, &KernelWinsize::default_console()).map(|()| 0),
+        _ => copy_from_user::<KernelTermios>(arg).map(|new_termios| {
+            if cmd == TCSETSF {
+                tty.flush_input();
+            }
+            tty.set_termios(&new_termios);
+            0
+        }),
+    })
+}
+
 /// I/O control operations on a file descriptor
 ///
 /// Handles terminal ioctls (TCGETS, TCSETS, TCSETSW, TCSETSF, TIOCGWINSZ,
@@ -1163,9 +1038,15 @@ pub fn sys_chdir(path_ptr: usize) -> SyscallResult {
 pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
     use crate::drivers::terminal::{
         self, KernelTermios, KernelWinsize, TCGETS, TCSETS, TCSETSF, TCSETSW, TIOCGPGRP,
-        TIOCGWINSZ, TIOCSPGRP, TIOCSWINSZ,
+        TIOCGWINSZ, TIOCNOTTY, TIOCSCTTY, TIOCSPGRP, TIOCSWINSZ,
     };
 
+    // FIONBIO and interface configuration requests on a socket
+    #[cfg(feature = "net")]
+    if let Some(result) = super::network_ext_syscalls::handle_socket_ioctl(fd, cmd, arg) {
+        return result;
+    }
+
     // Give PTY fds priority for TIOCGWINSZ / TIOCSWINSZ before the generic
     // terminal-only guard below rejects them.  For fd > 2, check whether the
     // fd refers to a PTY master or slave node.  If so, the PTY handler
@@ -1178,15 +1059,19 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
     }
 
     // DRM ioctl dispatch: check if the fd refers to a /dev/dri/* device.
-    // Also handle evdev ioctls for /dev/input/event* devices.
+    // Also handle evdev ioctls for /dev/input/event* devices. These drivers
+    // still access the argument in place, so at least make sure the
+    // argument struct (its size is encoded in the request) is user memory.
     if fd > 2 {
         if let Some(proc) = process::current_process() {
             let file_table = proc.file_table.lock();
             if let Some(file) = file_table.get(fd as crate::fs::file::FileDescriptor) {
                 if let Some(ref path) = file.path {
+                    #[cfg(feature = "graphics")]
                     if path.contains("dri/") {
                         // DRM ioctl -- dispatch to drm_ioctl module
                         drop(file_table);
+                        access_ok(arg, ioc_size(cmd), Access::Write)?;
                         return match crate::graphics::drm_ioctl::drm_ioctl_dispatch(
                             fd as i32,
                             cmd as u64,
@@ -1195,7 +1080,8 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
                             Ok(v) => Ok(v as usize),
                             Err(_) => Err(SyscallError::InvalidArgument),
                         };
-                    } else if path.contains("input/event") {
+                    }
+                    if path.contains("input/event") {
                         // evdev ioctl -- extract minor from path
                         let minor = if path.ends_with("event0") {
                             64u32
@@ -1205,6 +1091,7 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
                             return Err(SyscallError::InvalidArgument);
                         };
                         drop(file_table);
+                        access_ok(arg, ioc_size(cmd), Access::Write)?;
                         return match crate::drivers::evdev::handle_ioctl(
                             minor,
                             cmd as u32,
@@ -1219,13 +1106,38 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
         }
     }
 
+    // Serial ports other than the console keep their own termios, also when
+    // a getty has made one its stdin/stdout.
+    let serial_port = process::current_process().and_then(|proc| {
+        let file_table = proc.file_table.lock();
+        let path = file_table
+            .get(fd as crate::fs::file::FileDescriptor)?
+            .path
+            .clone()?;
+        crate::drivers::serial::get(path.strip_prefix("/dev/")?)
+    });
+    if let Some(tty) = serial_port.filter(|tty| !tty.is_console()) {
+        if let Some(result) = serial_ioctl(&tty, cmd, arg) {
+            return result;
+        }
+    }
+
     // Terminal ioctls are only valid on terminal fds (0=stdin, 1=stdout,
     // 2=stderr which are connected to the serial console). Regular files
     // opened via open() must return ENOTTY so that isatty() returns false
     // and BFD/stdio treat them as seekable files, not terminal streams.
     let is_terminal_cmd = matches!(
         cmd,
-        TIOCGWINSZ | TIOCSWINSZ | TCGETS | TCSETS | TCSETSW | TCSETSF | TIOCGPGRP | TIOCSPGRP
+        TIOCGWINSZ
+            | TIOCSWINSZ
+            | TCGETS
+            | TCSETS
+            | TCSETSW
+            | TCSETSF
+            | TIOCGPGRP
+            | TIOCSPGRP
+            | TIOCSCTTY
+            | TIOCNOTTY
     );
     if is_terminal_cmd && fd > 2 {
         return Err(SyscallError::NotATerminal);
@@ -1237,13 +1149,8 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<KernelWinsize>(arg)?;
-
             let ws = terminal::get_winsize_snapshot();
-            // SAFETY: arg was validated as aligned, non-null, and in user space.
-            unsafe {
-                core::ptr::write(arg as *mut KernelWinsize, ws);
-            }
+            copy_to_user(arg, &ws)?;
             Ok(0)
         }
         TIOCSWINSZ => {
@@ -1251,11 +1158,14 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<KernelWinsize>(arg)?;
-
-            // SAFETY: arg was validated as aligned, non-null, and in user space.
-            let ws = unsafe { core::ptr::read(arg as *const KernelWinsize) };
+            let ws: KernelWinsize = copy_from_user(arg)?;
+            let old = terminal::get_winsize_snapshot();
             terminal::set_winsize(&ws);
+            // A resize is reported to the foreground job (SIGWINCH)
+            let pgid = super::process::console_foreground_pgid();
+            if (old.ws_row, old.ws_col) != (ws.ws_row, ws.ws_col) && pgid != 0 {
+                let _ = send_signal_to_pgid(pgid, crate::process::exit::signals::SIGWINCH);
+            }
             Ok(0)
         }
         TCGETS => {
@@ -1263,13 +1173,8 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<KernelTermios>(arg)?;
-
             let termios = terminal::get_termios_snapshot();
-            // SAFETY: arg was validated above.
-            unsafe {
-                core::ptr::write(arg as *mut KernelTermios, termios);
-            }
+            copy_to_user(arg, &termios)?;
             Ok(0)
         }
         TCSETS => {
@@ -1277,10 +1182,7 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<KernelTermios>(arg)?;
-
-            // SAFETY: arg was validated as aligned, non-null, and in user space.
-            let new_termios = unsafe { core::ptr::read(arg as *const KernelTermios) };
+            let new_termios: KernelTermios = copy_from_user(arg)?;
             terminal::set_termios(&new_termios);
             Ok(0)
         }
@@ -1291,23 +1193,18 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<KernelTermios>(arg)?;
-
-            // SAFETY: arg was validated above.
-            let new_termios = unsafe { core::ptr::read(arg as *const KernelTermios) };
+            let new_termios: KernelTermios = copy_from_user(arg)?;
             terminal::set_termios(&new_termios);
             Ok(0)
         }
         TCSETSF => {
             // Set terminal attributes after draining output and flushing input.
-            // For serial console, no input buffer to flush, so equivalent to TCSETS.
+            // Output is synchronous; unread console input is discarded.
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<KernelTermios>(arg)?;
-
-            // SAFETY: arg was validated above.
-            let new_termios = unsafe { core::ptr::read(arg as *const KernelTermios) };
+            let new_termios: KernelTermios = copy_from_user(arg)?;
+            crate::drivers::serial::flush_console_input();
             terminal::set_termios(&new_termios);
             Ok(0)
         }
@@ -1316,16 +1213,12 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<i32>(arg)?;
             let pgid = if let Some(proc) = process::current_process() {
                 proc.pgid.load(core::sync::atomic::Ordering::Acquire) as i32
             } else {
                 1
             };
-            // SAFETY: arg was validated above.
-            unsafe {
-                core::ptr::write(arg as *mut i32, pgid);
-            }
+            copy_to_user(arg, &pgid)?;
             Ok(0)
         }
         TIOCSPGRP => {
@@ -1333,9 +1226,14 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             if arg == 0 {
                 return Err(SyscallError::InvalidPointer);
             }
-            validate_user_ptr_typed::<i32>(arg)?;
+            access_ok(arg, core::mem::size_of::<i32>(), Access::Read)?;
             Ok(0)
         }
+        TIOCSCTTY => {
+            // arg == 1 lets root take the console from another live session
+            super::process::console_acquire(arg == 1)
+        }
+        TIOCNOTTY => super::process::console_release(),
         _ => {
             // ENOTTY -- not a terminal or unsupported ioctl
             Err(SyscallError::InvalidArgument)
@@ -1343,6 +1241,11 @@ pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
     }
 }
 
+/// Argument size encoded in a Linux-style `_IOC` ioctl request.
+fn ioc_size(cmd: usize) -> usize {
+    (cmd >> 16) & 0x3FFF
+}
+
 /// Send a signal to a process
 ///
 /// # Arguments
@@ -1402,28 +1305,7 @@ fn send_signal_to_pgid(pgid: u64, signal: i32) -> SyscallResult {
 /// Helper: read a NUL-terminated path from user space into an alloc::String.
 #[cfg(feature = "alloc")]
 pub(crate) fn read_user_path(ptr: usize) -> Result<alloc::string::String, SyscallError> {
-    validate_user_string_ptr(ptr)?;
-
-    // SAFETY: ptr was validated as non-null and in user-space above. We read
-    // bytes until null terminator or 4096-byte limit. The caller must provide
-    // a valid null-terminated string in mapped user memory.
-    let bytes = unsafe {
-        let mut v = Vec::new();
-        let mut p = ptr as *const u8;
-        for _ in 0..4096 {
-            let b = core::ptr::read_volatile(p);
-            if b == 0 {
-                break;
-            }
-            v.push(b);
-            p = p.add(1);
-        }
-        v
-    };
-
-    core::str::from_utf8(&bytes)
-        .map(alloc::string::String::from)
-        .map_err(|_| SyscallError::InvalidArgument)
+    strncpy_from_user(ptr, PATH_MAX)
 }
 
 /// Stat a file by path (syscall 150).
@@ -1437,20 +1319,17 @@ pub(crate) fn read_user_path(ptr: usize) -> Result<alloc::string::String, Syscal
 /// # Returns
 /// 0 on success.
 pub fn sys_stat_path(path_ptr: usize, stat_buf: usize) -> SyscallResult {
-    validate_user_ptr_typed::<FileStat>(stat_buf)?;
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Read, true)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
     let node = vfs_guard.resolve_path(&path).map_err(map_resolve_err)?;
 
     let metadata = node.metadata().map_err(|_| SyscallError::InvalidState)?;
-    let stat = fill_stat(&metadata);
+    let stat = fill_stat(&metadata, node.link_count());
 
-    // SAFETY: stat_buf was validated as non-null, in user-space, and aligned.
-    unsafe {
-        core::ptr::write(stat_buf as *mut FileStat, stat);
-    }
+    copy_to_user(stat_buf, &stat)?;
     Ok(0)
 }
 
@@ -1467,8 +1346,8 @@ pub fn sys_stat_path(path_ptr: usize, stat_buf: usize) -> SyscallResult {
 /// # Returns
 /// 0 on success.
 pub fn sys_lstat(path_ptr: usize, stat_buf: usize) -> SyscallResult {
-    validate_user_ptr_typed::<FileStat>(stat_buf)?;
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Read, false)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1477,12 +1356,9 @@ pub fn sys_lstat(path_ptr: usize, stat_buf: usize) -> SyscallResult {
         .map_err(map_resolve_err)?;
 
     let metadata = node.metadata().map_err(|_| SyscallError::InvalidState)?;
-    let stat = fill_stat(&metadata);
+    let stat = fill_stat(&metadata, node.link_count());
 
-    // SAFETY: stat_buf was validated as non-null, in user-space, and aligned.
-    unsafe {
-        core::ptr::write(stat_buf as *mut FileStat, stat);
-    }
+    copy_to_user(stat_buf, &stat)?;
     Ok(0)
 }
 
@@ -1514,10 +1390,11 @@ pub fn sys_lstat(path_ptr: usize, stat_buf: usize) -> SyscallResult {
 ///   `NotImplemented`, which is mapped to `InvalidArgument` here.
 pub fn sys_readlink(path_ptr: usize, buf: usize, bufsiz: usize) -> SyscallResult {
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Read, false)?;
     if bufsiz == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(buf, bufsiz)?;
+    access_ok(buf, bufsiz, Access::Write)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1535,13 +1412,7 @@ pub fn sys_readlink(path_ptr: usize, buf: usize, bufsiz: usize) -> SyscallResult
     let bytes = target.as_bytes();
     let to_copy = core::cmp::min(bytes.len(), bufsiz);
 
-    // SAFETY: buf was validated above as non-null and in user space with
-    // at least bufsiz bytes. We copy at most bufsiz bytes of the target
-    // string. copy_slice_to_user handles the raw pointer write.
-    unsafe {
-        crate::syscall::userspace::copy_slice_to_user(buf, &bytes[..to_copy])
-            .map_err(|_| SyscallError::InvalidArgument)?;
-    }
+    copy_bytes_to_user(buf, &bytes[..to_copy])?;
 
     Ok(to_copy)
 }
@@ -1559,6 +1430,7 @@ pub fn sys_readlink(path_ptr: usize, buf: usize, bufsiz: usize) -> SyscallResult
 /// 0 if accessible, error otherwise.
 pub fn sys_access(path_ptr: usize, mode: usize) -> SyscallResult {
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Read, true)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1566,28 +1438,17 @@ pub fn sys_access(path_ptr: usize, mode: usize) -> SyscallResult {
     // Check if the file exists (F_OK = 0)
     let node = vfs_guard.resolve_path(&path).map_err(map_resolve_err)?;
 
-    // For non-zero mode, check permissions against metadata
+    // For non-zero mode, check permissions. Per POSIX, access() checks
+    // against the real (not effective) uid/gid.
     if mode != 0 {
-        let metadata = node.metadata().map_err(|_| SyscallError::InvalidState)?;
-
-        // Determine the caller's uid.  Root (uid 0) bypasses all checks.
-        let caller_uid = crate::process::current_process()
-            .map(|p| p.uid)
-            .unwrap_or(0);
-
-        if caller_uid != 0 {
-            let perms = &metadata.permissions;
-            // R_OK=4, W_OK=2, X_OK=1
-            if mode & 4 != 0 && !perms.other_read {
-                return Err(SyscallError::PermissionDenied);
-            }
-            if mode & 2 != 0 && !perms.other_write {
-                return Err(SyscallError::PermissionDenied);
-            }
-            if mode & 1 != 0 && !perms.other_exec {
-                return Err(SyscallError::PermissionDenied);
-            }
-        }
+        let mut creds = current_credentials();
+        creds.euid = creds.ruid;
+        creds.egid = creds.rgid;
+        require_access(
+            &*node,
+            &creds,
+            mode as u32 & (ACCESS_READ | ACCESS_WRITE | ACCESS_EXEC),
+        )?;
     }
 
     Ok(0)
@@ -1604,17 +1465,81 @@ pub fn sys_access(path_ptr: usize, mode: usize) -> SyscallResult {
 pub fn sys_rename(old_ptr: usize, new_ptr: usize) -> SyscallResult {
     let old_path = read_user_path(old_ptr)?;
     let new_path = read_user_path(new_ptr)?;
+    rename_paths(&old_path, &new_path)
+}
+
+/// Map errors from `VfsNode::rename` / `VfsNode::link`, keeping EINVAL for
+/// rejected names and directory loops.
+fn map_rename_err(e: KernelError) -> SyscallError {
+    match e {
+        KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+        e => super::map_kernel_error(e),
+    }
+}
 
-    // Rename as copy + delete (VFS has no native rename)
-    // Use the free-standing fs helpers which handle locking internally.
-    let data = crate::fs::read_file(&old_path).map_err(|_| SyscallError::ResourceNotFound)?;
-    crate::fs::write_file(&new_path, &data).map_err(|_| SyscallError::InvalidState)?;
+/// Shared implementation of `rename` and `renameat` on absolute paths.
+///
+/// Uses the filesystem's native rename, which replaces `new_path` atomically.
+/// Filesystems without one fall back to copy + unlink, which only works for
+/// regular files.
+pub(crate) fn rename_paths(old_path: &str, new_path: &str) -> SyscallResult {
+    require_sandbox(AT_FDCWD, old_path, AccessType::Write, false)?;
+    require_sandbox(AT_FDCWD, new_path, AccessType::Write, false)?;
+    let creds = current_credentials();
+    require_parent_writable(old_path, &creds)?;
+    require_parent_writable(new_path, &creds)?;
+
+    let (old_parent_path, old_name) = split_path(old_path)?;
+    let (new_parent_path, new_name) = split_path(new_path)?;
 
     let vfs_lock = vfs()?;
-    vfs_lock
-        .read()
-        .unlink(&old_path)
-        .map_err(|_| SyscallError::InvalidState)?;
+    let result = {
+        let vfs_guard = vfs_lock.read();
+        let old_parent = vfs_guard
+            .resolve_path(&old_parent_path)
+            .map_err(map_resolve_err)?;
+        let new_parent = vfs_guard
+            .resolve_path(&new_parent_path)
+            .map_err(map_resolve_err)?;
+        old_parent.rename(&old_name, &*new_parent, &new_name)
+    };
+
+    match result {
+        Ok(()) => Ok(0),
+        Err(KernelError::NotImplemented { .. }) => {
+            let data =
+                crate::fs::read_file(old_path).map_err(|_| SyscallError::ResourceNotFound)?;
+            crate::fs::write_file(new_path, &data).map_err(|_| SyscallError::InvalidState)?;
+            vfs_lock
+                .read()
+                .unlink(old_path)
+                .map_err(|_| SyscallError::InvalidState)?;
+            Ok(0)
+        }
+        Err(e) => Err(map_rename_err(e)),
+    }
+}
+
+/// Shared implementation of `link` and `linkat` on absolute paths.
+pub(crate) fn link_paths(old_path: &str, new_path: &str) -> SyscallResult {
+    require_sandbox(AT_FDCWD, old_path, AccessType::Write, true)?;
+    require_sandbox(AT_FDCWD, new_path, AccessType::Write, false)?;
+    require_parent_writable(new_path, &current_credentials())?;
+
+    let vfs_lock = vfs()?;
+    let vfs_guard = vfs_lock.read();
+
+    // Resolve the old path to get the target node
+    let target = vfs_guard.resolve_path(old_path).map_err(map_resolve_err)?;
+
+    // Split new_path into parent dir + name
+    let (parent_path, link_name) = split_path(new_path)?;
+
+    let parent = vfs_guard
+        .resolve_path(&parent_path)
+        .map_err(map_resolve_err)?;
+
+    parent.link(&link_name, target).map_err(map_rename_err)?;
 
     Ok(0)
 }
@@ -1628,6 +1553,8 @@ pub fn sys_rename(old_ptr: usize, new_ptr: usize) -> SyscallResult {
 /// 0 on success.
 pub fn sys_unlink(path_ptr: usize) -> SyscallResult {
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Write, false)?;
+    require_parent_writable(&path, &current_credentials())?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1644,11 +1571,13 @@ pub fn sys_unlink(path_ptr: usize) -> SyscallResult {
 ///
 /// # Arguments
 /// - `fd`: File descriptor.
-/// - `cmd`: Command (F_DUPFD=0, F_GETFD=1, F_SETFD=2, F_GETFL=3, F_SETFL=4).
+/// - `cmd`: Command (F_DUPFD=0, F_GETFD=1, F_SETFD=2, F_GETFL=3, F_SETFL=4,
+///   F_DUPFD_CLOEXEC=1030).
 /// - `arg`: Command-specific argument.
 ///
 /// # Returns
-/// Command-specific value on success.
+/// Command-specific value on success; EBADF for a closed `fd`, EINVAL for a
+/// F_DUPFD target at or above RLIMIT_NOFILE, EMFILE if no descriptor is free.
 pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
     const F_DUPFD: usize = 0;
     const F_GETFD: usize = 1;
@@ -1657,43 +1586,41 @@ pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
     const F_SETFL: usize = 4;
     const F_DUPFD_CLOEXEC: usize = 1030;
     const FD_CLOEXEC: usize = 1;
+    // Linux x86_64 ABI status flags, as in OpenFlags::from_bits
+    const O_APPEND: usize = 0x0400;
+    const O_NONBLOCK: usize = 0x0800;
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
 
     match cmd {
-        F_DUPFD => {
+        F_DUPFD | F_DUPFD_CLOEXEC => {
             // Duplicate fd to lowest available >= arg
-            match file_table.dup_at_least(fd, arg, false) {
-                Ok(new_fd) => Ok(new_fd),
-                Err(_) => Err(SyscallError::InvalidArgument),
-            }
-        }
-        F_DUPFD_CLOEXEC => {
-            // Duplicate fd to lowest available >= arg, with close-on-exec
-            match file_table.dup_at_least(fd, arg, true) {
-                Ok(new_fd) => Ok(new_fd),
-                Err(_) => Err(SyscallError::InvalidArgument),
-            }
+            file_table
+                .dup_at_least(fd, arg, cmd == F_DUPFD_CLOEXEC)
+                .map_err(|e| match e {
+                    KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+                    e => super::map_kernel_error(e),
+                })
         }
         F_GETFD => {
             // Get close-on-exec flag via FileTable API
-            match file_table.get_cloexec(fd) {
-                Ok(cloexec) => Ok(if cloexec { FD_CLOEXEC } else { 0 }),
-                Err(_) => Err(SyscallError::InvalidArgument),
-            }
+            let cloexec = file_table
+                .get_cloexec(fd)
+                .map_err(super::map_kernel_error)?;
+            Ok(if cloexec { FD_CLOEXEC } else { 0 })
         }
         F_SETFD => {
             // Set close-on-exec flag via FileTable API
             let cloexec = arg & FD_CLOEXEC != 0;
             file_table
                 .set_cloexec(fd, cloexec)
-                .map_err(|_| SyscallError::InvalidArgument)?;
+                .map_err(super::map_kernel_error)?;
             Ok(0)
         }
         F_GETFL => {
             // Get file status flags. Linux ABI: O_RDONLY=0, O_WRONLY=1, O_RDWR=2.
-            let file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
+            let file = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
             let mut flags: usize = if file.flags.read && file.flags.write {
                 2 // O_RDWR
             } else if file.flags.write {
@@ -1701,22 +1628,24 @@ pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
             } else {
                 0 // O_RDONLY
             };
-            if file.flags.append {
-                flags |= 0x0400; // O_APPEND
+            if file.append.load(core::sync::atomic::Ordering::Relaxed) {
+                flags |= O_APPEND;
             }
             if file.nonblock.load(core::sync::atomic::Ordering::Relaxed) {
-                flags |= 0x0800; // O_NONBLOCK
+                flags |= O_NONBLOCK;
             }
             Ok(flags)
         }
         F_SETFL => {
             // Set file status flags. Only O_APPEND and O_NONBLOCK can be
-            // changed after open (per POSIX). O_NONBLOCK is stored as an
-            // AtomicBool on the File for lock-free toggling.
-            let file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
-            let nonblock = (arg & 0x0800) != 0; // O_NONBLOCK
+            // changed after open (per POSIX); the rest of `arg` is ignored.
+            // Both live in AtomicBools on the File, which duplicated
+            // descriptors share.
+            let file = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
+            file.append
+                .store(arg & O_APPEND != 0, core::sync::atomic::Ordering::Relaxed);
             file.nonblock
-                .store(nonblock, core::sync::atomic::Ordering::Relaxed);
+                .store(arg & O_NONBLOCK != 0, core::sync::atomic::Ordering::Relaxed);
             Ok(0)
         }
         _ => Err(SyscallError::InvalidArgument),
@@ -1736,7 +1665,7 @@ pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SyscallResult {
 /// # Returns
 /// 0 on success.
 pub fn sys_pipe2(pipe_fds_ptr: usize, flags: usize) -> SyscallResult {
-    validate_user_buffer(pipe_fds_ptr, 2 * core::mem::size_of::<i32>())?;
+    access_ok(pipe_fds_ptr, 2 * core::mem::size_of::<i32>(), Access::Write)?;
 
     let cloexec = flags & 0x2000 != 0;
 
@@ -1770,13 +1699,7 @@ pub fn sys_pipe2(pipe_fds_ptr: usize, flags: usize) -> SyscallResult {
         })?;
 
     // Write [read_fd, write_fd] to user buffer as i32 (C int).
-    // SAFETY: pipe_fds_ptr was validated above as non-null and in user
-    // space with sufficient size for two i32 values.
-    unsafe {
-        let fds = pipe_fds_ptr as *mut i32;
-        *fds = read_fd as i32;
-        *fds.add(1) = write_fd as i32;
-    }
+    copy_to_user(pipe_fds_ptr, &[read_fd as i32, write_fd as i32])?;
 
     Ok(0)
 }
@@ -1810,7 +1733,7 @@ pub fn sys_dup3(old_fd: usize, new_fd: usize, flags: usize) -> SyscallResult {
 
     file_table
         .dup3(old_fd, new_fd, cloexec)
-        .map_err(|_| SyscallError::InvalidArgument)?;
+        .map_err(super::map_kernel_error)?;
 
     Ok(new_fd)
 }
@@ -1824,6 +1747,7 @@ pub fn sys_dup3(old_fd: usize, new_fd: usize, flags: usize) -> SyscallResult {
 /// Directory handle (fd) on success.
 pub fn sys_opendir(path_ptr: usize) -> SyscallResult {
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Read, true)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1870,7 +1794,7 @@ pub fn sys_readdir(fd: usize, entry_buf: usize, buf_size: usize) -> SyscallResul
     if buf_size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(entry_buf, buf_size)?;
+    access_ok(entry_buf, buf_size, Access::Write)?;
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
@@ -1897,22 +1821,17 @@ pub fn sys_readdir(fd: usize, entry_buf: usize, buf_size: usize) -> SyscallResul
     }
 
     // Write entry name + NUL terminator + node type byte to user buffer
-    // SAFETY: entry_buf was validated above as non-null and in user space
-    // with sufficient size. We write name_bytes.len() + 2 bytes total.
-    unsafe {
-        let dst = entry_buf as *mut u8;
-        core::ptr::copy_nonoverlapping(name_bytes.as_ptr(), dst, name_bytes.len());
-        *dst.add(name_bytes.len()) = 0; // NUL terminator
-        *dst.add(name_bytes.len() + 1) = match entry.node_type {
-            crate::fs::NodeType::File => 0,
-            crate::fs::NodeType::Directory => 1,
-            crate::fs::NodeType::CharDevice => 2,
-            crate::fs::NodeType::BlockDevice => 3,
-            crate::fs::NodeType::Symlink => 4,
-            crate::fs::NodeType::Pipe => 5,
-            _ => 0,
-        };
-    }
+    let type_byte: u8 = match entry.node_type {
+        crate::fs::NodeType::File => 0,
+        crate::fs::NodeType::Directory => 1,
+        crate::fs::NodeType::CharDevice => 2,
+        crate::fs::NodeType::BlockDevice => 3,
+        crate::fs::NodeType::Symlink => 4,
+        crate::fs::NodeType::Pipe => 5,
+        _ => 0,
+    };
+    copy_bytes_to_user(entry_buf, name_bytes)?;
+    copy_bytes_to_user(entry_buf + name_bytes.len(), &[0, type_byte])?;
 
     // Advance the file position to the next entry
     let _ = file_desc.seek(crate::fs::SeekFrom::Start(pos + 1));
@@ -1964,20 +1883,12 @@ pub fn sys_readv(fd: usize, iov_ptr: usize, iovcnt: usize) -> SyscallResult {
         return Err(SyscallError::InvalidArgument);
     }
 
-    // Validate the iovec array itself
-    let iov_size = iovcnt * core::mem::size_of::<Iovec>();
-    validate_user_buffer(iov_ptr, iov_size)?;
+    // Copy the iovec array in
+    let iovecs = copy_slice_from_user::<Iovec>(iov_ptr, iovcnt)?;
 
     let mut total_read = 0usize;
 
-    for i in 0..iovcnt {
-        // SAFETY: iov_ptr was validated above. Each Iovec is repr(C) with
-        // known size. We read iov entries one at a time within bounds.
-        let iov = unsafe {
-            let ptr = (iov_ptr as *const Iovec).add(i);
-            core::ptr::read(ptr)
-        };
-
+    for iov in iovecs {
         if iov.iov_len == 0 {
             continue;
         }
@@ -2021,20 +1932,12 @@ pub fn sys_writev(fd: usize, iov_ptr: usize, iovcnt: usize) -> SyscallResult {
         return Err(SyscallError::InvalidArgument);
     }
 
-    // Validate the iovec array itself
-    let iov_size = iovcnt * core::mem::size_of::<Iovec>();
-    validate_user_buffer(iov_ptr, iov_size)?;
+    // Copy the iovec array in
+    let iovecs = copy_slice_from_user::<Iovec>(iov_ptr, iovcnt)?;
 
     let mut total_written = 0usize;
 
-    for i in 0..iovcnt {
-        // SAFETY: iov_ptr was validated above. Each Iovec is repr(C) with
-        // known size. We read iov entries one at a time within bounds.
-        let iov = unsafe {
-            let ptr = (iov_ptr as *const Iovec).add(i);
-            core::ptr::read(ptr)
-        };
-
+    for iov in iovecs {
         if iov.iov_len == 0 {
             continue;
         }
@@ -2067,7 +1970,7 @@ pub fn sys_writev(fd: usize, iov_ptr: usize, iovcnt: usize) -> SyscallResult {
 
 /// AT_FDCWD sentinel: use process current working directory.
 /// Must match the C-side `#define AT_FDCWD (-100)` in syscall.h.
-const AT_FDCWD: usize = (-100isize) as usize;
+pub(crate) const AT_FDCWD: usize = (-100isize) as usize;
 
 /// Create a hard link (syscall 155).
 ///
@@ -2076,25 +1979,7 @@ const AT_FDCWD: usize = (-100isize) as usize;
 pub fn sys_link(old_ptr: usize, new_ptr: usize) -> SyscallResult {
     let old_path = read_user_path(old_ptr)?;
     let new_path = read_user_path(new_ptr)?;
-
-    let vfs_lock = vfs()?;
-    let vfs_guard = vfs_lock.read();
-
-    // Resolve the old path to get the target node
-    let target = vfs_guard.resolve_path(&old_path).map_err(map_resolve_err)?;
-
-    // Split new_path into parent dir + name
-    let (parent_path, link_name) = split_path(&new_path)?;
-
-    let parent = vfs_guard
-        .resolve_path(&parent_path)
-        .map_err(map_resolve_err)?;
-
-    parent
-        .link(&link_name, target)
-        .map_err(|_| SyscallError::InvalidArgument)?;
-
-    Ok(0)
+    link_paths(&old_path, &new_path)
 }
 
 /// Create a symbolic link (syscall 156).
@@ -2103,6 +1988,7 @@ pub fn sys_link(old_ptr: usize, new_ptr: usize) -> SyscallResult {
 pub fn sys_symlink(target_ptr: usize, link_ptr: usize) -> SyscallResult {
     let target = read_user_path(target_ptr)?;
     let link_path = read_user_path(link_ptr)?;
+    require_sandbox(AT_FDCWD, &link_path, AccessType::Write, false)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -2120,13 +2006,53 @@ pub fn sys_symlink(target_ptr: usize, link_ptr: usize) -> SyscallResult {
     Ok(0)
 }
 
+/// Require that `creds` own `node` (or are privileged), as needed to change
+/// its mode.
+pub(crate) fn require_owner(node: &dyn VfsNode, creds: &Credentials) -> Result<(), SyscallError> {
+    if creds.is_privileged() {
+        return Ok(());
+    }
+    match node.metadata() {
+        Ok(meta) if meta.uid != creds.euid => Err(SyscallError::PermissionDenied),
+        _ => Ok(()),
+    }
+}
+
+/// Apply a chown request to `node`.
+///
+/// `uid`/`gid` of `ID_UNCHANGED` (-1) keep the current value. Only a
+/// privileged caller may change the owner; the owner may change the group
+/// to one of its own groups.
+pub(crate) fn chown_node(node: &dyn VfsNode, uid: u32, gid: u32) -> SyscallResult {
+    use crate::process::creds::ID_UNCHANGED;
+
+    let creds = current_credentials();
+    let meta = node.metadata().map_err(super::map_kernel_error)?;
+    let new_uid = if uid == ID_UNCHANGED { meta.uid } else { uid };
+    let new_gid = if gid == ID_UNCHANGED { meta.gid } else { gid };
+
+    if !creds.is_privileged()
+        && (new_uid != meta.uid
+            || creds.euid != meta.uid
+            || (new_gid != meta.gid && !creds.in_group(new_gid)))
+    {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    node.chown(new_uid, new_gid)
+        .map_err(super::map_kernel_error)?;
+    Ok(0)
+}
+
 /// Change file permissions by path (syscall 185).
 pub fn sys_chmod(path_ptr: usize, mode: usize) -> SyscallResult {
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Write, true)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
     let node = vfs_guard.resolve_path(&path).map_err(map_resolve_err)?;
+    require_owner(&*node, &current_credentials())?;
 
     let perms = Permissions::from_mode(mode as u32);
     node.chmod(perms)
@@ -2140,6 +2066,7 @@ pub fn sys_fchmod(fd: usize, mode: usize) -> SyscallResult {
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
     let file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
+    require_owner(&*file.node, &proc.credentials())?;
 
     let perms = Permissions::from_mode(mode as u32);
     file.node
@@ -2172,6 +2099,7 @@ pub fn sys_umask(mask: usize) -> SyscallResult {
 /// Truncate a file by path (syscall 188).
 pub fn sys_truncate_path(path_ptr: usize, size: usize) -> SyscallResult {
     let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Write, true)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -2189,9 +2117,10 @@ pub fn sys_truncate_path(path_ptr: usize, size: usize) -> SyscallResult {
 ///
 /// Poll file descriptors for I/O readiness using VfsNode::poll_readiness().
 ///
-/// Checks each fd's actual buffer state (pipe occupancy, write-end closed,
-/// etc.) rather than always reporting ready. Supports timeout via busy-wait
-/// with scheduler yield (same approach as nanosleep).
+/// Checks each fd's actual state (pipe occupancy, write-end closed, a
+/// completed line on a terminal, etc.) rather than always reporting ready.
+/// Waits by yielding to the scheduler until a descriptor is ready, the
+/// timeout passes or a signal with a handler arrives (EINTR).
 ///
 /// # Arguments
 /// - `fds_ptr`: Pointer to array of PollFd structs.
@@ -2213,25 +2142,14 @@ pub fn sys_poll(fds_ptr: usize, nfds: usize, timeout_ms: usize) -> SyscallResult
         return Err(SyscallError::InvalidArgument);
     }
 
-    validate_user_buffer(fds_ptr, nfds * core::mem::size_of::<PollFd>())?;
+    let mut pollfds = copy_slice_from_user::<PollFd>(fds_ptr, nfds)?;
 
     let timeout_i32 = timeout_ms as i32;
-    let start = crate::timer::get_uptime_ms();
-    // Cap infinite wait to 30 seconds to prevent permanent hangs
-    let max_wait_ms: u64 = if timeout_i32 < 0 {
-        30_000
-    } else {
-        timeout_ms as u64
-    };
-
-    loop {
-        let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
-        let file_table = proc.file_table.lock();
+    let timeout = (timeout_i32 >= 0).then_some(timeout_ms as u64);
+    let ready_count = wait_for_ready(timeout, |file_table| {
         let mut ready_count = 0usize;
 
-        for i in 0..nfds {
-            // SAFETY: fds_ptr was validated above and PollFd is repr(C).
-            let pollfd = unsafe { &mut *((fds_ptr as *mut PollFd).add(i)) };
+        for pollfd in pollfds.iter_mut() {
             pollfd.revents = 0;
 
             if pollfd.fd < 0 {
@@ -2261,22 +2179,58 @@ pub fn sys_poll(fds_ptr: usize, nfds: usize, timeout_ms: usize) -> SyscallResult
                 ready_count += 1;
             }
         }
-        // Drop file_table lock before yielding
-        drop(file_table);
+        Ok(ready_count)
+    })?;
 
-        if ready_count > 0 || timeout_i32 == 0 {
-            return Ok(ready_count);
-        }
+    copy_slice_to_user(fds_ptr, &pollfds)?;
+    Ok(ready_count)
+}
+
+/// Longest a wait without a timeout lasts, to prevent permanent hangs.
+const MAX_READY_WAIT_MS: u64 = 30_000;
 
-        // Timeout expired?
-        if crate::timer::get_uptime_ms() - start >= max_wait_ms {
-            return Ok(0);
+/// Run `scan` over the caller's file table until it counts ready
+/// descriptors, `timeout_ms` passes (`None`: no timeout) or a signal with a
+/// handler is pending.
+///
+/// The file table is unlocked while the CPU is yielded.
+fn wait_for_ready(
+    timeout_ms: Option<u64>,
+    mut scan: impl FnMut(&crate::fs::file::FileTable) -> Result<usize, SyscallError>,
+) -> SyscallResult {
+    let start = crate::timer::get_uptime_ms();
+    let max_wait_ms = timeout_ms.unwrap_or(MAX_READY_WAIT_MS);
+
+    loop {
+        let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
+        let ready = scan(&proc.file_table.lock())?;
+
+        if ready > 0 || crate::timer::get_uptime_ms() - start >= max_wait_ms {
+            return Ok(ready);
+        }
+        if signal_interrupts_wait(proc) {
+            return Err(SyscallError::Interrupted);
         }
 
         crate::sched::yield_cpu();
     }
 }
 
+/// Whether an unblocked pending signal has a handler to run. Signals left
+/// at SIG_DFL or SIG_IGN do not end a wait.
+fn signal_interrupts_wait(proc: &process::Process) -> bool {
+    use core::sync::atomic::Ordering;
+
+    let pending =
+        proc.pending_signals.load(Ordering::Acquire) & !proc.signal_mask.load(Ordering::Acquire);
+    (1..32).any(|signum| {
+        pending & (1 << signum) != 0
+            && proc
+                .get_signal_handler(signum)
+                .is_some_and(|handler| handler > 1)
+    })
+}
+
 /// Poll event flags
 const POLLIN: i16 = 0x001;
 const POLLOUT: i16 = 0x004;
@@ -2355,6 +2309,7 @@ pub fn sys_openat(dirfd: usize, path_ptr: usize, flags: usize, mode: usize) -> S
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let open_flags = OpenFlags::from_bits(flags as u32).ok_or(SyscallError::InvalidArgument)?;
     let cloexec = (flags & 0x80000) != 0; // O_CLOEXEC
+    require_sandbox(AT_FDCWD, &abs_path, open_access(&open_flags), true)?;
 
     match vfs()?.read().open(&abs_path, open_flags) {
         Ok(node) => {
@@ -2396,8 +2351,7 @@ pub fn sys_openat(dirfd: usize, path_ptr: usize, flags: usize, mode: usize) -> S
 pub fn sys_fstatat(dirfd: usize, path_ptr: usize, stat_buf: usize, _flags: usize) -> SyscallResult {
     let rel_path = read_user_path(path_ptr)?;
     let abs_path = resolve_at_path(dirfd, &rel_path)?;
-
-    validate_user_ptr_typed::<FileStat>(stat_buf)?;
+    require_sandbox(AT_FDCWD, &abs_path, AccessType::Read, true)?;
 
     let vfs_lock = vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -2406,11 +2360,8 @@ pub fn sys_fstatat(dirfd: usize, path_ptr: usize, stat_buf: usize, _flags: usize
         .map_err(|_| SyscallError::ResourceNotFound)?;
 
     let metadata = node.metadata().map_err(|_| SyscallError::InvalidState)?;
-    let stat = fill_stat(&metadata);
-    // SAFETY: stat_buf was validated above.
-    unsafe {
-        core::ptr::write(stat_buf as *mut FileStat, stat);
-    }
+    let stat = fill_stat(&metadata, node.link_count());
+    copy_to_user(stat_buf, &stat)?;
     Ok(0)
 }
 
@@ -2420,6 +2371,7 @@ pub fn sys_fstatat(dirfd: usize, path_ptr: usize, stat_buf: usize, _flags: usize
 pub fn sys_unlinkat(dirfd: usize, path_ptr: usize, _flags: usize) -> SyscallResult {
     let rel_path = read_user_path(path_ptr)?;
     let abs_path = resolve_at_path(dirfd, &rel_path)?;
+    require_sandbox(AT_FDCWD, &abs_path, AccessType::Write, false)?;
 
     let vfs_lock = vfs()?;
     vfs_lock
@@ -2434,6 +2386,7 @@ pub fn sys_unlinkat(dirfd: usize, path_ptr: usize, _flags: usize) -> SyscallResu
 pub fn sys_mkdirat(dirfd: usize, path_ptr: usize, mode: usize) -> SyscallResult {
     let rel_path = read_user_path(path_ptr)?;
     let abs_path = resolve_at_path(dirfd, &rel_path)?;
+    require_sandbox(AT_FDCWD, &abs_path, AccessType::Write, false)?;
 
     let permissions = Permissions::from_mode(mode as u32);
     vfs()?
@@ -2455,16 +2408,7 @@ pub fn sys_renameat(
     let new_rel = read_user_path(new_ptr)?;
     let old_abs = resolve_at_path(olddirfd, &old_rel)?;
     let new_abs = resolve_at_path(newdirfd, &new_rel)?;
-
-    // Rename as copy + delete
-    let data = crate::fs::read_file(&old_abs).map_err(|_| SyscallError::ResourceNotFound)?;
-    crate::fs::write_file(&new_abs, &data).map_err(|_| SyscallError::InvalidState)?;
-    vfs()?
-        .read()
-        .unlink(&old_abs)
-        .map_err(|_| SyscallError::InvalidState)?;
-
-    Ok(0)
+    rename_paths(&old_abs, &new_abs)
 }
 
 /// Read from a file descriptor at a given offset without changing position
@@ -2473,17 +2417,17 @@ pub fn sys_pread(fd: usize, buf: usize, count: usize, offset: usize) -> SyscallR
     if count == 0 {
         return Ok(0);
     }
-    validate_user_buffer(buf, count)?;
+    let count = count.min(IO_BOUNCE_MAX);
+    access_ok(buf, count, Access::Write)?;
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
     let file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
 
     // Read directly at offset through the VfsNode, bypassing File position
-    // SAFETY: buf was validated above.
-    let buffer_slice = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, count) };
-    match file.node.read(offset, buffer_slice) {
-        Ok(n) => Ok(n),
+    let mut data = alloc::vec![0u8; count];
+    match file.node.read(offset, &mut data) {
+        Ok(n) => finish_read(buf, &data, n),
         Err(_) => Err(SyscallError::InvalidState),
     }
 }
@@ -2494,21 +2438,358 @@ pub fn sys_pwrite(fd: usize, buf: usize, count: usize, offset: usize) -> Syscall
     if count == 0 {
         return Ok(0);
     }
-    validate_user_buffer(buf, count)?;
+    let data = copy_slice_from_user::<u8>(buf, count.min(IO_BOUNCE_MAX))?;
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
     let file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
 
     // Write directly at offset through the VfsNode, bypassing File position
-    // SAFETY: buf was validated above.
-    let buffer_slice = unsafe { core::slice::from_raw_parts(buf as *const u8, count) };
-    match file.node.write(offset, buffer_slice) {
+    match file.node.write(offset, &data) {
         Ok(n) => Ok(n),
         Err(_) => Err(SyscallError::InvalidState),
     }
 }
 
+/// Resolve a `sendfile`/`splice` descriptor: an INET socket, which the
+/// zero-copy path drives through its socket ID, or any other open file.
+#[cfg(feature = "net")]
+fn splice_end(fd: usize) -> Result<SpliceEnd, SyscallError> {
+    let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = proc.file_table.lock();
+    let file = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
+    if let Some(socket) = file
+        .node
+        .as_any()
+        .and_then(|any| any.downcast_ref::<SocketNode>())
+    {
+        if let SocketHandle::Inet(id) = socket.handle() {
+            return Ok(SpliceEnd::InetSocket(id));
+        }
+    }
+    Ok(SpliceEnd::File(file))
+}
+
+/// Read the optional `off_t *` argument of `sendfile`/`splice`.
+#[cfg(feature = "net")]
+fn read_user_offset(ptr: usize) -> Result<Option<u64>, SyscallError> {
+    if ptr == 0 {
+        return Ok(None);
+    }
+    let off: i64 = copy_from_user(ptr)?;
+    u64::try_from(off)
+        .map(Some)
+        .map_err(|_| SyscallError::InvalidArgument)
+}
+
+/// Run a transfer and store the advanced offsets back to user space.
+#[cfg(feature = "net")]
+fn run_transfer(transfer: SendFile, offsets: [(usize, Option<u64>); 2]) -> SyscallResult {
+    let n = transfer.execute().map_err(|e| match e {
+        crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
+        e => super::map_kernel_error(e),
+    })?;
+    for (ptr, off) in offsets {
+        if let Some(off) = off {
+            copy_to_user(ptr, &((off + n as u64) as i64))?;
+        }
+    }
+    Ok(n)
+}
+
+/// Copy data from a file to a socket, pipe or file inside the kernel
+/// (syscall 370).
+///
+/// Linux ABI: `sendfile(out_fd, in_fd, offset_ptr, count)`. `out_fd` may be
+/// an INET socket. With `offset_ptr` set the input is read from `*offset_ptr`,
+/// which is advanced, and the file position of `in_fd` is left alone.
+#[cfg(feature = "net")]
+pub fn sys_sendfile(out_fd: usize, in_fd: usize, offset_ptr: usize, count: usize) -> SyscallResult {
+    if count == 0 {
+        return Ok(0);
+    }
+    let source = match splice_end(in_fd)? {
+        SpliceEnd::File(file) => SpliceEnd::File(file),
+        SpliceEnd::InetSocket(_) => return Err(SyscallError::InvalidArgument),
+    };
+    let dest = splice_end(out_fd)?;
+    let offset = read_user_offset(offset_ptr)?;
+
+    run_transfer(
+        SendFile::new(source, offset, dest, None, count),
+        [(offset_ptr, offset), (0, None)],
+    )
+}
+
+/// Move data into or out of a pipe inside the kernel (syscall 371).
+///
+/// Linux ABI: `splice(fd_in, off_in, fd_out, off_out, len)`; the flags
+/// argument is dropped by libc (all `SPLICE_F_*` flags are hints). One end
+/// must be a pipe and the pipe end takes no offset. The other end may be a
+/// file, another pipe or an INET socket.
+#[cfg(feature = "net")]
+pub fn sys_splice(
+    fd_in: usize,
+    off_in_ptr: usize,
+    fd_out: usize,
+    off_out_ptr: usize,
+    len: usize,
+) -> SyscallResult {
+    let source = splice_end(fd_in)?;
+    let dest = splice_end(fd_out)?;
+    let is_pipe = |end: &SpliceEnd| match end {
+        SpliceEnd::File(file) => crate::fs::pipe::is_pipe_node(&*file.node),
+        SpliceEnd::InetSocket(_) => false,
+    };
+    let (in_pipe, out_pipe) = (is_pipe(&source), is_pipe(&dest));
+    if !in_pipe && !out_pipe {
+        return Err(SyscallError::InvalidArgument);
+    }
+    if (in_pipe && off_in_ptr != 0) || (out_pipe && off_out_ptr != 0) {
+        return Err(SyscallError::InvalidArgument);
+    }
+    if len == 0 {
+        return Ok(0);
+    }
+    let off_in = read_user_offset(off_in_ptr)?;
+    let off_out = read_user_offset(off_out_ptr)?;
+
+    run_transfer(
+        SendFile::new(source, off_in, dest, off_out, len),
+        [(off_in_ptr, off_in), (off_out_ptr, off_out)],
+    )
+}
+
+// =========================================================================
+// Extended attributes (372-379)
+// =========================================================================
+
+/// Read and validate an attribute name argument.
+fn read_xattr_name(ptr: usize) -> Result<alloc::string::String, SyscallError> {
+    let name = read_user_path(ptr)?;
+    crate::fs::xattr::validate_name(&name).map_err(|_| SyscallError::InvalidArgument)?;
+    Ok(name)
+}
+
+/// Check that the caller may read (or, with `write`, modify) the attribute
+/// `name` of `node`.
+///
+/// `user.` attributes follow the file's read/write permission, ACLs may be
+/// changed by the owner, `trusted.` is privileged-only and the remaining
+/// `security.`/`system.` attributes are world-readable but privileged to
+/// change (capability labels live there).
+fn require_xattr_access(node: &dyn VfsNode, name: &str, write: bool) -> Result<(), SyscallError> {
+    let creds = current_credentials();
+    if name.starts_with("user.") {
+        let mask = if write { ACCESS_WRITE } else { ACCESS_READ };
+        return require_access(node, &creds, mask);
+    }
+    if name == ACL_ACCESS_XATTR || name == ACL_DEFAULT_XATTR {
+        return if write {
+            require_owner(node, &creds)
+        } else {
+            Ok(())
+        };
+    }
+    if (write || name.starts_with("trusted.")) && !creds.is_privileged() {
+        return Err(SyscallError::PermissionDenied);
+    }
+    Ok(())
+}
+
+/// Map an xattr operation error; a missing attribute is `ENODATA`.
+fn map_xattr_err(err: KernelError) -> SyscallError {
+    match err {
+        KernelError::FsError(FsError::NotFound) => SyscallError::NoData,
+        KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+        KernelError::ResourceExhausted { .. } => SyscallError::NoSpace,
+        KernelError::NotImplemented { .. } => SyscallError::NotImplemented,
+        e => super::map_kernel_error(e),
+    }
+}
+
+/// Copy an attribute value or name list to user space.
+///
+/// A `size` of 0 only reports the required length; a buffer that is too
+/// small fails with `ERANGE`.
+fn copy_xattr_out(ptr: usize, size: usize, data: &[u8]) -> SyscallResult {
+    if size == 0 {
+        return Ok(data.len());
+    }
+    if data.len() > size {
+        return Err(SyscallError::OutOfRange);
+    }
+    copy_bytes_to_user(ptr, data)?;
+    Ok(data.len())
+}
+
+fn getxattr_node(
+    node: &dyn VfsNode,
+    name_ptr: usize,
+    value_ptr: usize,
+    size: usize,
+) -> SyscallResult {
+    let name = read_xattr_name(name_ptr)?;
+    require_xattr_access(node, &name, false)?;
+    let value = node.get_xattr(&name).map_err(map_xattr_err)?;
+    copy_xattr_out(value_ptr, size, &value)
+}
+
+fn setxattr_node(
+    node: &dyn VfsNode,
+    name_ptr: usize,
+    value_ptr: usize,
+    size: usize,
+    flags: usize,
+) -> SyscallResult {
+    let name = read_xattr_name(name_ptr)?;
+    if flags as u32 & !(XATTR_CREATE | XATTR_REPLACE) != 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    if size > XATTR_MAX_VALUE_SIZE {
+        return Err(SyscallError::ArgumentListTooLong);
+    }
+    let value = copy_slice_from_user::<u8>(value_ptr, size)?;
+    require_xattr_access(node, &name, true)?;
+
+    let acl = if name == ACL_ACCESS_XATTR || name == ACL_DEFAULT_XATTR {
+        Some(Acl::parse(&value).map_err(|_| SyscallError::InvalidArgument)?)
+    } else {
+        None
+    };
+    if name == ACL_DEFAULT_XATTR && node.node_type() != crate::fs::NodeType::Directory {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    node.set_xattr(&name, &value, flags as u32)
+        .map_err(map_xattr_err)?;
+
+    // Keep the mode bits in sync with a new access ACL so that mode-only
+    // consumers (stat, filesystems without ACL support) see its effect.
+    if let (Some(acl), true) = (acl, name == ACL_ACCESS_XATTR) {
+        let _ = node.chmod(Permissions::from_mode(acl.mode_bits()));
+    }
+    Ok(0)
+}
+
+fn listxattr_node(node: &dyn VfsNode, list_ptr: usize, size: usize) -> SyscallResult {
+    let privileged = current_credentials().is_privileged();
+    let names = node.list_xattr().map_err(map_xattr_err)?;
+    let mut list = Vec::new();
+    for name in names
+        .iter()
+        .filter(|n| privileged || !n.starts_with("trusted."))
+    {
+        list.extend_from_slice(name.as_bytes());
+        list.push(0);
+    }
+    copy_xattr_out(list_ptr, size, &list)
+}
+
+fn removexattr_node(node: &dyn VfsNode, name_ptr: usize) -> SyscallResult {
+    let name = read_xattr_name(name_ptr)?;
+    require_xattr_access(node, &name, true)?;
+    node.remove_xattr(&name).map_err(map_xattr_err)?;
+    Ok(0)
+}
+
+/// Resolve the path argument of the path-based xattr calls.
+fn xattr_path_node(
+    path_ptr: usize,
+    access: AccessType,
+) -> Result<alloc::sync::Arc<dyn VfsNode>, SyscallError> {
+    let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, access, true)?;
+    let vfs_guard = vfs()?.read();
+    vfs_guard.resolve_path(&path).map_err(map_resolve_err)
+}
+
+/// Resolve the fd argument of the fd-based xattr calls.
+fn xattr_fd_node(fd: usize) -> Result<alloc::sync::Arc<dyn VfsNode>, SyscallError> {
+    let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = proc.file_table.lock();
+    let file = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
+    Ok(file.node.clone())
+}
+
+/// Get an extended attribute by path (syscall 372).
+///
+/// Returns the value length. With `size == 0` only the length is returned.
+pub fn sys_getxattr(
+    path_ptr: usize,
+    name_ptr: usize,
+    value_ptr: usize,
+    size: usize,
+) -> SyscallResult {
+    getxattr_node(
+        &*xattr_path_node(path_ptr, AccessType::Read)?,
+        name_ptr,
+        value_ptr,
+        size,
+    )
+}
+
+/// Set an extended attribute by path (syscall 373).
+///
+/// `flags` takes `XATTR_CREATE` / `XATTR_REPLACE`. Setting
+/// `system.posix_acl_access` validates the ACL and updates the mode bits.
+pub fn sys_setxattr(
+    path_ptr: usize,
+    name_ptr: usize,
+    value_ptr: usize,
+    size: usize,
+    flags: usize,
+) -> SyscallResult {
+    setxattr_node(
+        &*xattr_path_node(path_ptr, AccessType::Write)?,
+        name_ptr,
+        value_ptr,
+        size,
+        flags,
+    )
+}
+
+/// List extended attribute names by path as a NUL-separated list
+/// (syscall 374).
+pub fn sys_listxattr(path_ptr: usize, list_ptr: usize, size: usize) -> SyscallResult {
+    listxattr_node(
+        &*xattr_path_node(path_ptr, AccessType::Read)?,
+        list_ptr,
+        size,
+    )
+}
+
+/// Remove an extended attribute by path (syscall 375).
+pub fn sys_removexattr(path_ptr: usize, name_ptr: usize) -> SyscallResult {
+    removexattr_node(&*xattr_path_node(path_ptr, AccessType::Write)?, name_ptr)
+}
+
+/// Get an extended attribute of an open file (syscall 376).
+pub fn sys_fgetxattr(fd: usize, name_ptr: usize, value_ptr: usize, size: usize) -> SyscallResult {
+    getxattr_node(&*xattr_fd_node(fd)?, name_ptr, value_ptr, size)
+}
+
+/// Set an extended attribute of an open file (syscall 377).
+pub fn sys_fsetxattr(
+    fd: usize,
+    name_ptr: usize,
+    value_ptr: usize,
+    size: usize,
+    flags: usize,
+) -> SyscallResult {
+    setxattr_node(&*xattr_fd_node(fd)?, name_ptr, value_ptr, size, flags)
+}
+
+/// List extended attribute names of an open file (syscall 378).
+pub fn sys_flistxattr(fd: usize, list_ptr: usize, size: usize) -> SyscallResult {
+    listxattr_node(&*xattr_fd_node(fd)?, list_ptr, size)
+}
+
+/// Remove an extended attribute of an open file (syscall 379).
+pub fn sys_fremovexattr(fd: usize, name_ptr: usize) -> SyscallResult {
+    removexattr_node(&*xattr_fd_node(fd)?, name_ptr)
+}
+
 /// Helper: split a path into (parent_dir, basename).
 pub(crate) fn split_path(
     path: &str,
@@ -2544,28 +2825,25 @@ pub(crate) fn split_path(
 
 /// Change ownership of a file by path (syscall 197).
 ///
-/// Stub: accepts but ignores — no real UID/GID enforcement yet.
+/// Pass -1 for `uid` or `gid` to leave it unchanged.
 pub fn sys_chown(path_ptr: usize, uid: usize, gid: usize) -> SyscallResult {
-    let _path = read_user_path(path_ptr)?;
-    let _uid = uid as u32;
-    let _gid = gid as u32;
-    // No-op: accept but don't enforce ownership changes
-    Ok(0)
+    let path = read_user_path(path_ptr)?;
+    require_sandbox(AT_FDCWD, &path, AccessType::Write, true)?;
+
+    let vfs_lock = vfs()?;
+    let vfs_guard = vfs_lock.read();
+    let node = vfs_guard.resolve_path(&path).map_err(map_resolve_err)?;
+    chown_node(&*node, uid as u32, gid as u32)
 }
 
 /// Change ownership of a file by file descriptor (syscall 198).
 ///
-/// Stub: accepts but ignores — no real UID/GID enforcement yet.
+/// Pass -1 for `uid` or `gid` to leave it unchanged.
 pub fn sys_fchown(fd: usize, uid: usize, gid: usize) -> SyscallResult {
-    let _fd = fd;
-    let _uid = uid as u32;
-    let _gid = gid as u32;
-    // Verify fd is valid
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
-    let _file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
-    // No-op: accept but don't enforce ownership changes
-    Ok(0)
+    let file = file_table.get(fd).ok_or(SyscallError::InvalidArgument)?;
+    chown_node(&*file.node, uid as u32, gid as u32)
 }
 
 /// Create a special or ordinary file (syscall 199).
@@ -2578,57 +2856,83 @@ pub fn sys_mknod(path_ptr: usize, _mode: usize, _dev: usize) -> SyscallResult {
 
 /// Synchronous I/O multiplexing (syscall 200).
 ///
-/// Scans fd_set bitmaps for set bits and checks whether the corresponding
-/// file descriptors exist in the current process's file table. Files and
-/// pipes are always considered ready (same simplification as `sys_poll`).
+/// Waits like `sys_poll` for a descriptor in `readfds` to become readable
+/// (or hung up) or one in `writefds` to become writable, then leaves only
+/// the ready descriptors set. No exceptional conditions are reported, so
+/// `exceptfds` comes back empty. A descriptor that is not open fails the
+/// call with EBADF.
+///
+/// `timeout_ptr` points to a `struct timeval`; null waits without limit.
 pub fn sys_select(
     nfds: usize,
     readfds_ptr: usize,
     writefds_ptr: usize,
-    _exceptfds_ptr: usize,
-    _timeout_ptr: usize,
+    exceptfds_ptr: usize,
+    timeout_ptr: usize,
 ) -> SyscallResult {
-    // fd_set is a bitmap: 1 bit per fd, packed into usize-width words.
-    // FD_SETSIZE is typically 1024, but we cap at nfds.
+    // fd_set is a bitmap: 1 bit per fd, packed into bytes. FD_SETSIZE is
+    // 1024, so nfds is capped there.
     let nfds = nfds.min(1024);
-    if nfds == 0 {
-        return Ok(0);
-    }
-
-    // Number of bytes needed for the bitmap
     let bytes_needed = nfds.div_ceil(8);
-    let mut ready_count: usize = 0;
 
-    // Helper: scan an fd_set bitmap and count ready fds.
-    // All existing fds are considered ready (files/pipes always ready).
-    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
-    let file_table = process.file_table.lock();
+    let timeout = if timeout_ptr == 0 {
+        None
+    } else {
+        let tv: [i64; 2] = copy_from_user(timeout_ptr)?;
+        if tv[0] < 0 || !(0..1_000_000).contains(&tv[1]) {
+            return Err(SyscallError::InvalidArgument);
+        }
+        Some(tv[0] as u64 * 1000 + tv[1] as u64 / 1000)
+    };
 
-    for fdset_ptr in [readfds_ptr, writefds_ptr] {
-        if fdset_ptr == 0 {
-            continue;
+    let read_fd_set = |ptr: usize| -> Result<alloc::vec::Vec<u8>, SyscallError> {
+        if ptr == 0 {
+            Ok(alloc::vec![0u8; bytes_needed])
+        } else {
+            copy_slice_from_user::<u8>(ptr, bytes_needed)
         }
-        validate_user_buffer(fdset_ptr, bytes_needed)?;
+    };
+    let want_read = read_fd_set(readfds_ptr)?;
+    let want_write = read_fd_set(writefds_ptr)?;
+    let want_except = read_fd_set(exceptfds_ptr)?;
+    let mut ready_read = alloc::vec![0u8; bytes_needed];
+    let mut ready_write = alloc::vec![0u8; bytes_needed];
+
+    let ready_count = wait_for_ready(timeout, |file_table| {
+        let mut ready_count = 0usize;
+        ready_read.fill(0);
+        ready_write.fill(0);
+
         for fd in 0..nfds {
-            let byte_idx = fd / 8;
-            let bit_idx = fd % 8;
-            // SAFETY: fdset_ptr validated above; byte_idx < bytes_needed.
-            let byte_val = unsafe { core::ptr::read_volatile((fdset_ptr + byte_idx) as *const u8) };
-            if byte_val & (1 << bit_idx) != 0 {
-                // Check if this fd exists in the file table
-                if file_table.get(fd).is_some() {
-                    ready_count += 1;
-                } else {
-                    // Clear the bit for fds that don't exist
-                    let cleared = byte_val & !(1 << bit_idx);
-                    // SAFETY: same pointer, validated above.
-                    unsafe {
-                        core::ptr::write_volatile((fdset_ptr + byte_idx) as *mut u8, cleared);
-                    }
-                }
+            let (byte_idx, bit) = (fd / 8, 1u8 << (fd % 8));
+            let wants_read = want_read[byte_idx] & bit != 0;
+            let wants_write = want_write[byte_idx] & bit != 0;
+            if !wants_read && !wants_write && want_except[byte_idx] & bit == 0 {
+                continue;
+            }
+            let file = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
+            let readiness = file.node.poll_readiness();
+            // POLLIN, POLLERR or POLLHUP: a read will not block
+            if wants_read && readiness & (0x0001 | 0x0008 | 0x0010) != 0 {
+                ready_read[byte_idx] |= bit;
+                ready_count += 1;
+            }
+            if wants_write && readiness & 0x0004 != 0 {
+                ready_write[byte_idx] |= bit;
+                ready_count += 1;
             }
         }
-    }
+        Ok(ready_count)
+    })?;
 
+    if readfds_ptr != 0 {
+        copy_bytes_to_user(readfds_ptr, &ready_read)?;
+    }
+    if writefds_ptr != 0 {
+        copy_bytes_to_user(writefds_ptr, &ready_write)?;
+    }
+    if exceptfds_ptr != 0 {
+        copy_bytes_to_user(exceptfds_ptr, &alloc::vec![0u8; bytes_needed])?;
+    }
     Ok(ready_count)
 }
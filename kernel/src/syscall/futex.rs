@@ -15,7 +15,10 @@ use spin::Mutex;
 use crate::{
     arch::timer::get_ticks,
     process, sched,
-    syscall::{userspace::validate_user_ptr, SyscallError},
+    syscall::{
+        uaccess::{access_ok, copy_from_user, copy_to_user, Access},
+        SyscallError,
+    },
 };
 
 // Bit positions for FUTEX_WAKE_OP operation encoding (Linux-compatible)
@@ -105,14 +108,7 @@ pub fn sys_futex_wait(
     if uaddr == 0 || uaddr & 0x3 != 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    // Must reside in user space (single validation -- no duplicate call)
-    validate_user_ptr(uaddr as *const u32, core::mem::size_of::<u32>())?;
-
-    // SAFETY: `uaddr` has been validated as a properly-aligned, mapped,
-    // user-space pointer to a u32.  We use `read_volatile` because another
-    // thread sharing this address space may concurrently modify the futex
-    // word; a non-volatile read could be elided or reordered by the compiler.
-    let cur = unsafe { core::ptr::read_volatile(uaddr as *const u32) };
+    let cur: u32 = copy_from_user(uaddr)?;
     if cur != expected {
         return Err(SyscallError::WouldBlock);
     }
@@ -136,11 +132,7 @@ pub fn sys_futex_wait(
             // the layout; for WAIT_BITSET we treat `aux` as the mask instead.
             return Err(SyscallError::InvalidArgument);
         }
-        validate_user_ptr(timeout_ptr as *const u64, core::mem::size_of::<u64>())?;
-        // SAFETY: `timeout_ptr` has been validated as a properly-aligned,
-        // mapped, user-space pointer to a u64.  Volatile read is used because
-        // the caller could theoretically share this memory with another thread.
-        let rel = unsafe { core::ptr::read_volatile(timeout_ptr as *const u64) };
+        let rel: u64 = copy_from_user(timeout_ptr)?;
         // If op uses absolute time (FUTEX_CLOCK_REALTIME bit), treat rel as absolute
         // ticks
         if (op & 0x100) != 0 {
@@ -251,7 +243,7 @@ pub fn sys_futex_wake(
         return Err(SyscallError::InvalidArgument);
     }
 
-    validate_user_ptr(uaddr as *const u32, core::mem::size_of::<u32>())?;
+    access_ok(uaddr, core::mem::size_of::<u32>(), Access::Read)?;
 
     // Interpret the wake bitset: 0 means the caller did not supply one
     // (plain FUTEX_WAKE), so default to match-any.
@@ -341,13 +333,13 @@ pub fn sys_futex_dispatch(
     if uaddr == 0 || uaddr & 0x3 != 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_ptr(uaddr as *const u32, core::mem::size_of::<u32>())?;
+    access_ok(uaddr, core::mem::size_of::<u32>(), Access::Read)?;
 
     if (op as u32 & FUTEX_REQUEUE) != 0 || (op as u32 & FUTEX_WAKE_OP) != 0 {
         if uaddr2 == 0 || uaddr2 & 0x3 != 0 {
             return Err(SyscallError::InvalidArgument);
         }
-        validate_user_ptr(uaddr2 as *const u32, core::mem::size_of::<u32>())?;
+        access_ok(uaddr2, core::mem::size_of::<u32>(), Access::Read)?;
     }
 
     match (op as u32) & 0xF {
@@ -394,8 +386,7 @@ pub fn sys_futex_wake_op(
         return Err(SyscallError::InvalidArgument);
     }
 
-    validate_user_ptr(uaddr as *const u32, core::mem::size_of::<u32>())?;
-    validate_user_ptr(uaddr2 as *const u32, core::mem::size_of::<u32>())?;
+    access_ok(uaddr, core::mem::size_of::<u32>(), Access::Read)?;
 
     // Decode op
     let op_code = ((op as u32) & FUTEX_OP_MASK) >> 24;
@@ -403,10 +394,7 @@ pub fn sys_futex_wake_op(
     let oparg = (op as u32) & FUTEX_OPARG_MASK;
     let cmparg = ((op >> 12) & FUTEX_OPARG_MASK as usize) as u32;
 
-    // SAFETY: `uaddr2` has been validated as a properly-aligned, mapped,
-    // user-space pointer.  Volatile read is required because another thread
-    // may concurrently modify this memory location.
-    let cur = unsafe { core::ptr::read_volatile(uaddr2 as *const u32) };
+    let cur: u32 = copy_from_user(uaddr2)?;
     let new_val = match op_code {
         FUTEX_OP_SET => oparg,
         FUTEX_OP_ADD => cur.wrapping_add(oparg),
@@ -415,12 +403,7 @@ pub fn sys_futex_wake_op(
         FUTEX_OP_XOR => cur ^ oparg,
         _ => return Err(SyscallError::InvalidArgument),
     };
-    // SAFETY: Same validation as above.  Volatile write is required because
-    // other threads may be reading this memory concurrently (e.g. in a
-    // FUTEX_WAIT spin).
-    unsafe {
-        core::ptr::write_volatile(uaddr2 as *mut u32, new_val);
-    }
+    copy_to_user(uaddr2, &new_val)?;
 
     // Compare
     let cmp_ok = match cmp_code {
@@ -471,8 +454,8 @@ pub fn sys_futex_requeue(
     if uaddr == uaddr2 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_ptr(uaddr as *const u32, core::mem::size_of::<u32>())?;
-    validate_user_ptr(uaddr2 as *const u32, core::mem::size_of::<u32>())?;
+    access_ok(uaddr, core::mem::size_of::<u32>(), Access::Read)?;
+    access_ok(uaddr2, core::mem::size_of::<u32>(), Access::Read)?;
 
     let pid = process::current_process()
         .ok_or(SyscallError::InvalidState)?
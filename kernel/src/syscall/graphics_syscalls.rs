@@ -1,25 +1,43 @@
 //! Graphics and input syscall handlers (Phase 6).
 //!
-//! Syscalls 230-234: framebuffer info, framebuffer map, input polling/reading,
-//! and double-buffer swap.
+//! Syscalls 230-239: framebuffer info, framebuffer map, input polling/reading,
+//! double-buffer swap, screenshots / screen recording, desktop notifications,
+//! client windows, input injection, and vsync-paced page flips.
 
-use super::{validate_user_ptr_typed, SyscallError, SyscallResult};
-use crate::graphics::framebuffer::FbInfo;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "desktop")]
+use super::filesystem::{read_user_path, require_sandbox, resolve_at_path, AT_FDCWD};
+use super::{
+    map_kernel_error,
+    uaccess::{
+        access_ok, copy_bytes_from_user, copy_slice_from_user, copy_slice_to_user, copy_to_user,
+        Access,
+    },
+    SyscallError, SyscallResult,
+};
+#[cfg(feature = "desktop")]
+use crate::{
+    desktop::{client_window, screenshot},
+    security::AccessType,
+    services::notification_ipc::{self, NotificationIpcServer, NotificationMessage},
+};
+use crate::{
+    graphics::{framebuffer::FbInfo, vsync_sw},
+    process,
+};
 
 /// Get framebuffer information.
 ///
 /// # Arguments
 /// - `info_ptr`: User-space pointer to `FbInfo` struct to fill.
 pub(super) fn sys_fb_get_info(info_ptr: usize) -> SyscallResult {
-    validate_user_ptr_typed::<FbInfo>(info_ptr)?;
-
-    let fb_info = crate::graphics::framebuffer::get_fb_info().ok_or(SyscallError::InvalidState)?;
-
-    // SAFETY: info_ptr validated as non-null, aligned, and in user space.
-    unsafe {
-        *(info_ptr as *mut FbInfo) = fb_info;
-    }
-
+    let fb_info: FbInfo =
+        crate::graphics::framebuffer::get_fb_info().ok_or(SyscallError::InvalidState)?;
+    copy_to_user(info_ptr, &fb_info)?;
     Ok(0)
 }
 
@@ -94,24 +112,19 @@ pub(super) fn sys_input_read(events_ptr: usize, max_count: usize) -> SyscallResu
     let byte_size = max_count
         .checked_mul(core::mem::size_of::<InputEvent>())
         .ok_or(SyscallError::InvalidArgument)?;
-    super::validate_user_buffer(events_ptr, byte_size)?;
-
-    let mut count = 0usize;
-    let events = events_ptr as *mut InputEvent;
+    // Check up front so a bad buffer does not consume queued events.
+    access_ok(events_ptr, byte_size, Access::Write)?;
 
-    while count < max_count {
-        if let Some(event) = crate::drivers::input_event::read_event() {
-            // SAFETY: events_ptr validated for max_count entries.
-            unsafe {
-                events.add(count).write(event);
-            }
-            count += 1;
-        } else {
-            break;
+    let mut events: Vec<InputEvent> = Vec::new();
+    while events.len() < max_count {
+        match crate::drivers::input_event::read_event() {
+            Some(event) => events.push(event),
+            None => break,
         }
     }
 
-    Ok(count)
+    copy_slice_to_user(events_ptr, &events)?;
+    Ok(events.len())
 }
 
 /// Swap framebuffer (blit back-buffer to display).
@@ -119,3 +132,349 @@ pub(super) fn sys_fb_swap() -> SyscallResult {
     crate::graphics::fbcon::flush();
     Ok(0)
 }
+
+/// Window argument selecting the focused window (-1 in C).
+const SCREENSHOT_FOCUSED_WINDOW: usize = usize::MAX;
+
+/// Operation selected by the first `screenshot` argument.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotOp {
+    /// Write the screen (`arg2` = 0) or window `arg2` to the path `arg1`:
+    /// PNG, or BMP if the name ends in `.bmp`. Returns bytes written.
+    Capture = 0,
+    /// Record the screen (`arg2` = 0) or window `arg2` into the directory
+    /// `arg1`, every `arg3`-th frame (0 = default), stopping after `arg4`
+    /// frames (0 = when told to).
+    RecordStart = 1,
+    /// Stop recording. Returns frames written.
+    RecordStop = 2,
+    /// Frames written so far by the running recording.
+    RecordStatus = 3,
+}
+
+impl TryFrom<usize> for ScreenshotOp {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ScreenshotOp::Capture),
+            1 => Ok(ScreenshotOp::RecordStart),
+            2 => Ok(ScreenshotOp::RecordStop),
+            3 => Ok(ScreenshotOp::RecordStatus),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Capture screenshots and screen recordings (root only).
+///
+/// The window argument is a window manager ID, 0 for the whole screen, or
+/// -1 for the focused window.
+///
+/// Returns `InvalidState` when the desktop is not running or, for the
+/// recording queries, when nothing is being recorded.
+#[cfg(feature = "desktop")]
+pub(super) fn sys_screenshot(
+    op: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+) -> SyscallResult {
+    let op = ScreenshotOp::try_from(op).map_err(|_| SyscallError::InvalidArgument)?;
+    let caller = process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+    let window = match arg2 {
+        0 => None,
+        SCREENSHOT_FOCUSED_WINDOW => Some(
+            crate::desktop::window_manager::with_window_manager(|wm| wm.get_focused_window_id())
+                .flatten()
+                .ok_or(SyscallError::ResourceNotFound)?,
+        ),
+        wid => Some(wid as u32),
+    };
+
+    match op {
+        ScreenshotOp::Capture => {
+            let path = resolve_at_path(AT_FDCWD, &read_user_path(arg1)?)?;
+            require_sandbox(AT_FDCWD, &path, AccessType::Write, true)?;
+            screenshot::save_screenshot(&path, window).map_err(map_kernel_error)
+        }
+        ScreenshotOp::RecordStart => {
+            let dir = resolve_at_path(AT_FDCWD, &read_user_path(arg1)?)?;
+            require_sandbox(AT_FDCWD, &dir, AccessType::Write, true)?;
+            screenshot::start_recording(&dir, window, arg3 as u32, arg4 as u32)
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        ScreenshotOp::RecordStop => screenshot::stop_recording()
+            .map(|frames| frames as usize)
+            .ok_or(SyscallError::InvalidState),
+        ScreenshotOp::RecordStatus => screenshot::recording_frames()
+            .map(|frames| frames as usize)
+            .ok_or(SyscallError::InvalidState),
+    }
+}
+
+/// Send a request to the notification service.
+///
+/// `buf`/`len` hold a message in the `notification_ipc` wire format. Returns
+/// the new ID for `Notify`, the active count for `GetActive`, a status code
+/// for `Status`, and 0 otherwise. A reply endpoint in the message is ignored:
+/// callers here hold no capability for it, so they poll with `Status`.
+#[cfg(feature = "desktop")]
+pub(super) fn sys_notify(buf: usize, len: usize) -> SyscallResult {
+    if len > notification_ipc::MAX_MESSAGE_SIZE {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let bytes: Vec<u8> = copy_slice_from_user(buf, len)?;
+    let mut msg = NotificationMessage::decode(&bytes).ok_or(SyscallError::InvalidArgument)?;
+    msg.reply_endpoint = None;
+    NotificationIpcServer::new()
+        .handle_message(&msg)
+        .map(|value| value as usize)
+        .map_err(map_kernel_error)
+}
+
+/// Longest window title accepted from user space, in bytes.
+#[cfg(feature = "desktop")]
+const MAX_TITLE_LEN: usize = 256;
+
+/// Operation selected by the first `window` argument.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowOp {
+    /// Open a window with `arg1` x `arg2` pixels of content and the title
+    /// at `arg3`/`arg4`. Returns the window ID.
+    Create = 0,
+    /// Close window `arg1`.
+    Destroy = 1,
+    /// Show `arg3` bytes of XRGB8888 pixels at `arg2` in window `arg1`. The
+    /// length must be exactly width * height * 4.
+    Present = 2,
+    /// Copy up to `arg3` queued events of window `arg1` to the
+    /// `client_window::ClientEvent` array at `arg2`. Returns the count.
+    Poll = 3,
+    /// Set the title of window `arg1` to the string at `arg2`/`arg3`.
+    SetTitle = 4,
+    /// Replace the `client_window::FLAG_*` bits of window `arg1` with
+    /// `arg2`.
+    SetFlags = 5,
+}
+
+impl TryFrom<usize> for WindowOp {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(WindowOp::Create),
+            1 => Ok(WindowOp::Destroy),
+            2 => Ok(WindowOp::Present),
+            3 => Ok(WindowOp::Poll),
+            4 => Ok(WindowOp::SetTitle),
+            5 => Ok(WindowOp::SetFlags),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Copy a window title in from user space.
+#[cfg(feature = "desktop")]
+fn read_user_title(ptr: usize, len: usize) -> Result<alloc::string::String, SyscallError> {
+    if len > MAX_TITLE_LEN {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let bytes: Vec<u8> = copy_slice_from_user(ptr, len)?;
+    alloc::string::String::from_utf8(bytes).map_err(|_| SyscallError::InvalidArgument)
+}
+
+/// Create and drive a desktop window owned by the calling process.
+///
+/// Windows can only be used by the process that created them and are
+/// closed when it exits. Returns `InvalidState` when the desktop is not
+/// running.
+#[cfg(feature = "desktop")]
+pub(super) fn sys_window(
+    op: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+) -> SyscallResult {
+    let op = WindowOp::try_from(op).map_err(|_| SyscallError::InvalidArgument)?;
+    let owner = process::current_process()
+        .ok_or(SyscallError::InvalidState)?
+        .pid
+        .0;
+    let wid = arg1 as u32;
+
+    match op {
+        WindowOp::Create => {
+            let (Ok(width), Ok(height)) = (u32::try_from(arg1), u32::try_from(arg2)) else {
+                return Err(SyscallError::InvalidArgument);
+            };
+            let title = read_user_title(arg3, arg4)?;
+            client_window::create(owner, width, height, &title)
+                .map(|wid| wid as usize)
+                .map_err(map_kernel_error)
+        }
+        WindowOp::Destroy => {
+            client_window::with_client_windows(|reg| reg.destroy(owner, wid))
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        WindowOp::Present => {
+            if arg3 > (client_window::MAX_DIMENSION as usize).pow(2) * 4 {
+                return Err(SyscallError::InvalidArgument);
+            }
+            let pixels: Vec<u8> = copy_slice_from_user(arg2, arg3)?;
+            client_window::with_client_windows(|reg| reg.present(owner, wid, pixels))
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        WindowOp::Poll => {
+            let max = arg3.min(256);
+            if max == 0 {
+                return Ok(0);
+            }
+            let size = max * core::mem::size_of::<client_window::ClientEvent>();
+            // Check up front so a bad buffer does not consume queued events.
+            access_ok(arg2, size, Access::Write)?;
+            let events = client_window::with_client_windows(|reg| reg.poll(owner, wid, max))
+                .map_err(map_kernel_error)?;
+            copy_slice_to_user(arg2, &events)?;
+            Ok(events.len())
+        }
+        WindowOp::SetTitle => {
+            let title = read_user_title(arg2, arg3)?;
+            client_window::set_title(owner, wid, &title).map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        WindowOp::SetFlags => {
+            let flags = u32::try_from(arg2).map_err(|_| SyscallError::InvalidArgument)?;
+            client_window::with_client_windows(|reg| reg.set_flags(owner, wid, flags))
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+    }
+}
+
+/// Most events accepted by one `input_inject` call.
+const MAX_INJECT_EVENTS: usize = 64;
+
+/// Operation selected by the first `input_inject` argument.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputInjectOp {
+    /// Feed the `arg2` `InputEvent`s at `arg1` into the input stream.
+    /// Needs the input-injection capability (or root).
+    Inject = 0,
+    /// Give process `arg1` the input-injection capability. Root only.
+    Grant = 1,
+}
+
+impl TryFrom<usize> for InputInjectOp {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InputInjectOp::Inject),
+            1 => Ok(InputInjectOp::Grant),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Inject synthetic keyboard and pointer input, e.g. from an on-screen
+/// keyboard.
+pub(super) fn sys_input_inject(op: usize, arg1: usize, arg2: usize) -> SyscallResult {
+    use crate::drivers::input_event::{self, InputEvent};
+
+    let op = InputInjectOp::try_from(op).map_err(|_| SyscallError::InvalidArgument)?;
+    let caller = process::current_process().ok_or(SyscallError::InvalidState)?;
+
+    match op {
+        InputInjectOp::Inject => {
+            if caller.euid() != 0 && !input_event::may_inject(caller.pid) {
+                return Err(SyscallError::PermissionDenied);
+            }
+            if arg2 == 0 {
+                return Ok(0);
+            }
+            if arg2 > MAX_INJECT_EVENTS {
+                return Err(SyscallError::InvalidArgument);
+            }
+            let events: Vec<InputEvent> = copy_slice_from_user(arg1, arg2)?;
+            input_event::inject(&events).map_err(map_kernel_error)?;
+            Ok(events.len())
+        }
+        InputInjectOp::Grant => {
+            if caller.euid() != 0 {
+                return Err(SyscallError::PermissionDenied);
+            }
+            input_event::grant_inject(process::ProcessId(arg1 as u64)).map_err(map_kernel_error)?;
+            Ok(0)
+        }
+    }
+}
+
+/// `fb_flip` flag: present at the next vblank instead of immediately.
+pub const FB_FLIP_VSYNC: usize = 1;
+
+/// Present a full frame from user memory.
+///
+/// `buf` holds `width * height` 32-bit pixels in the format reported by
+/// `fb_get_info`, rows packed without padding. Under virtio-gpu the frame is
+/// copied into a back buffer and flipped onto the scanout with one command,
+/// so it never tears; otherwise it is copied into the linear framebuffer.
+/// With `FB_FLIP_VSYNC` the call first sleeps until the next vblank, which
+/// paces the caller to the display rate.
+///
+/// # Returns
+/// The vblank sequence number the frame was presented in.
+pub(super) fn sys_fb_flip(buf: usize, flags: usize) -> SyscallResult {
+    if flags & !FB_FLIP_VSYNC != 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let info = crate::graphics::framebuffer::get_fb_info().ok_or(SyscallError::InvalidState)?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    let row_len = width * 4;
+    access_ok(buf, row_len * height, Access::Read)?;
+
+    let mut seq = vsync_sw::vblank_seq();
+    if flags & FB_FLIP_VSYNC != 0 {
+        seq = vsync_sw::wait_for_vblank(seq);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if crate::drivers::virtio_gpu::get_display_size() == Some((info.width, info.height)) {
+        crate::drivers::virtio_gpu::with_driver(|gpu| {
+            let back = gpu.back_buffer_mut().ok_or(SyscallError::InvalidState)?;
+            // SAFETY: The back buffer holds width * height u32 pixels; any
+            // byte pattern is a valid u32.
+            let bytes = unsafe {
+                core::slice::from_raw_parts_mut(back.as_mut_ptr().cast::<u8>(), back.len() * 4)
+            };
+            copy_bytes_from_user(buf, &mut bytes[..row_len * height])?;
+            gpu.flip().map_err(map_kernel_error)
+        })
+        .ok_or(SyscallError::InvalidState)??;
+        return Ok(seq as usize);
+    }
+
+    crate::graphics::framebuffer::with_framebuffer(|fb| {
+        let base = fb.buffer_ptr().ok_or(SyscallError::InvalidState)? as *mut u8;
+        for y in 0..height {
+            // SAFETY: The framebuffer mapping covers pitch * height bytes and
+            // each row is width * 4 <= pitch bytes.
+            let row = unsafe {
+                core::slice::from_raw_parts_mut(base.add(y * info.pitch as usize), row_len)
+            };
+            copy_bytes_from_user(buf + y * row_len, row)?;
+        }
+        Ok(seq as usize)
+    })
+}
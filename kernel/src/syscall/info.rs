@@ -1,10 +1,15 @@
-//! Syscall for getting kernel information.
+//! Syscalls for getting kernel information and reading the kernel log.
 
 use core::mem::size_of;
 
-#[allow(unused_imports)]
+use veridian_abi::{AbiVersion, ABI_VERSION};
+
 use crate::{
-    syscall::{validate_user_buffer, validate_user_ptr_typed, SyscallError, SyscallResult},
+    log_service::LogLevel,
+    syscall::{
+        uaccess::{copy_slice_to_user, copy_to_user},
+        SyscallError, SyscallResult,
+    },
     utils::version::{get_version_info, KernelVersionInfo},
 };
 
@@ -16,20 +21,7 @@ use crate::{
 /// # Returns
 /// A `SyscallResult` indicating the outcome of the operation.
 pub fn sys_get_kernel_info(buf: usize) -> SyscallResult {
-    // Validate buffer is in user space and properly aligned for KernelVersionInfo
-    validate_user_ptr_typed::<KernelVersionInfo>(buf)?;
-
-    let version_info = get_version_info();
-
-    // Copy the version info to the user buffer
-    // SAFETY: buf was validated as non-null, in user-space, properly sized,
-    // and aligned for KernelVersionInfo above. The struct is Copy and
-    // repr(C), so the write through the pointer is well-defined.
-    unsafe {
-        let user_buf = buf as *mut KernelVersionInfo;
-        *user_buf = version_info;
-    }
-
+    copy_to_user(buf, &get_version_info())?;
     Ok(size_of::<KernelVersionInfo>())
 }
 
@@ -44,27 +36,22 @@ const UTSNAME_SIZE: usize = UTSNAME_LENGTH * 6;
 /// # Arguments
 /// * `buf` - Pointer to a user-space utsname struct (325 bytes).
 pub fn sys_uname(buf: usize) -> SyscallResult {
-    validate_user_buffer(buf, UTSNAME_SIZE)?;
-
-    let user_buf = buf as *mut u8;
+    let mut utsname = [0u8; UTSNAME_SIZE];
 
     // Helper: write a string into a fixed-size field, NUL-padded.
-    let write_field = |offset: usize, value: &[u8]| {
-        let field =
-            unsafe { core::slice::from_raw_parts_mut(user_buf.add(offset), UTSNAME_LENGTH) };
+    let mut write_field = |offset: usize, value: &[u8]| {
         let len = core::cmp::min(value.len(), UTSNAME_LENGTH - 1);
-        field[..len].copy_from_slice(&value[..len]);
-        // Zero the rest
-        for byte in &mut field[len..] {
-            *byte = 0;
-        }
+        utsname[offset..offset + len].copy_from_slice(&value[..len]);
     };
 
     // Field offsets: sysname=0, nodename=65, release=130, version=195, machine=260
     write_field(0, b"VeridianOS");
     write_field(UTSNAME_LENGTH, b"veridian");
     write_field(UTSNAME_LENGTH * 2, b"0.5.0");
-    write_field(UTSNAME_LENGTH * 3, b"#1 SMP");
+    write_field(
+        UTSNAME_LENGTH * 3,
+        crate::utils::version::UNAME_VERSION.as_bytes(),
+    );
 
     #[cfg(target_arch = "x86_64")]
     write_field(UTSNAME_LENGTH * 4, b"x86_64");
@@ -76,5 +63,221 @@ pub fn sys_uname(buf: usize) -> SyscallResult {
     // domainname (6th field) -- empty string (no NIS domain)
     write_field(UTSNAME_LENGTH * 5, b"");
 
+    copy_slice_to_user(buf, &utsname)?;
     Ok(0)
 }
+
+/// `SYS_DMESG` flag bits selecting levels (0 = all levels).
+const DMESG_LEVEL_MASK: usize = 0xff;
+/// `SYS_DMESG` flag: clear the log after reading it (root only).
+const DMESG_CLEAR: usize = 0x100;
+
+/// Read the kernel log (SYS_DMESG = 81).
+///
+/// Copies the buffered kernel messages into `buf` as text, one line per
+/// entry. The low byte of `flags` selects levels: 0 for all, otherwise one
+/// more than the most verbose [`LogLevel`] to include. If the log does not
+/// fit, the newest whole lines that do are copied. With a null `buf` the
+/// size of the whole log is returned instead.
+///
+/// # Returns
+/// Number of bytes copied (or needed, for a null `buf`).
+pub fn sys_dmesg(buf: usize, len: usize, flags: usize) -> SyscallResult {
+    if flags & !(DMESG_LEVEL_MASK | DMESG_CLEAR) != 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let clear = flags & DMESG_CLEAR != 0;
+    if clear {
+        let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+        if caller.euid() != 0 {
+            return Err(SyscallError::PermissionDenied);
+        }
+    }
+
+    let max_level = match flags & DMESG_LEVEL_MASK {
+        0 => LogLevel::Trace,
+        n => LogLevel::from_u8((n - 1) as u8),
+    };
+    let text = crate::log_service::log_read(max_level);
+    let text = text.as_bytes();
+
+    let copied = if buf == 0 {
+        text.len()
+    } else {
+        // Keep the newest lines: skip to the first line start that leaves
+        // no more than `len` bytes.
+        let mut start = text.len().saturating_sub(len);
+        if start > 0 && text[start - 1] != b'\n' {
+            start = text[start..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(text.len(), |i| start + i + 1);
+        }
+        let tail = &text[start..];
+        copy_slice_to_user(buf, tail)?;
+        tail.len()
+    };
+
+    if clear {
+        crate::log_service::log_clear();
+    }
+    Ok(copied)
+}
+
+/// `SYS_CRASH_DUMP` flag: mark the dump as seen after reading it.
+const CRASH_DUMP_ACK: usize = 1;
+
+/// Read the crash dump of the previous boot (SYS_CRASH_DUMP = 83).
+///
+/// Copies the dump that [`crate::crash`] saved when the previous boot
+/// panicked into `buf`; with a null `buf` only its size is returned. With
+/// [`CRASH_DUMP_ACK`] the dump is marked as seen, so the next boot no
+/// longer warns about it. Root only.
+///
+/// # Returns
+/// Size of the dump, or `ResourceNotFound` if there is none.
+pub fn sys_crash_dump(buf: usize, len: usize, flags: usize) -> SyscallResult {
+    if flags & !CRASH_DUMP_ACK != 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let dump = crate::crash::previous().ok_or(SyscallError::ResourceNotFound)?;
+    if buf != 0 {
+        if len < dump.len() {
+            return Err(SyscallError::InvalidArgument);
+        }
+        copy_slice_to_user(buf, &dump)?;
+    }
+    if flags & CRASH_DUMP_ACK != 0 {
+        crate::crash::acknowledge().map_err(|_| SyscallError::IoError)?;
+    }
+    Ok(dump.len())
+}
+
+/// `SYS_WATCHDOG` operations. Bits 8 and up of the operation select the
+/// channel.
+const WATCHDOG_ARM: usize = 0;
+const WATCHDOG_PET: usize = 1;
+const WATCHDOG_DISARM: usize = 2;
+const WATCHDOG_FIRE: usize = 3;
+const WATCHDOG_REMAINING: usize = 4;
+const WATCHDOG_CHANNEL_SHIFT: usize = 8;
+
+/// Control the software watchdog (SYS_WATCHDOG = 84).
+///
+/// `WATCHDOG_ARM` starts the countdown of a channel with a timeout of `arg`
+/// ms, and each `WATCHDOG_PET` restarts it; the machine resets if any armed
+/// channel runs out (see [`crate::drivers::watchdog`]). `WATCHDOG_FIRE`
+/// resets it right away. Root only.
+///
+/// # Returns
+/// The ms left for `WATCHDOG_REMAINING` (`ResourceNotFound` while disarmed),
+/// otherwise 0. Arming fails with `NotImplemented` under `watchdog=off`, and
+/// any operation with `InvalidArgument` for a channel out of range.
+pub fn sys_watchdog(op: usize, arg: usize) -> SyscallResult {
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let channel = op >> WATCHDOG_CHANNEL_SHIFT;
+    if channel >= crate::drivers::watchdog::CHANNELS {
+        return Err(SyscallError::InvalidArgument);
+    }
+    match op & ((1 << WATCHDOG_CHANNEL_SHIFT) - 1) {
+        WATCHDOG_ARM => {
+            crate::drivers::watchdog::arm(channel, arg as u64).map_err(|e| match e {
+                crate::error::KernelError::OperationNotSupported { .. } => {
+                    SyscallError::NotImplemented
+                }
+                _ => SyscallError::InvalidArgument,
+            })?
+        }
+        WATCHDOG_PET => crate::drivers::watchdog::pet(channel)?,
+        WATCHDOG_DISARM => crate::drivers::watchdog::disarm(channel)?,
+        WATCHDOG_FIRE => {
+            crate::drivers::watchdog::fire();
+            return Err(SyscallError::IoError);
+        }
+        WATCHDOG_REMAINING => {
+            return crate::drivers::watchdog::remaining_ms(channel)?
+                .map(|ms| ms as usize)
+                .ok_or(SyscallError::ResourceNotFound)
+        }
+        _ => return Err(SyscallError::InvalidArgument),
+    }
+    Ok(0)
+}
+
+/// `SYS_REBOOT` commands.
+const REBOOT_CMD_RESTART: usize = 1;
+const REBOOT_CMD_POWER_OFF: usize = 2;
+const REBOOT_CMD_HALT: usize = 3;
+const REBOOT_CMD_SUSPEND: usize = 4;
+const REBOOT_CMD_PENDING: usize = 5;
+
+/// Power off, reboot, halt or suspend the machine (SYS_REBOOT = 85).
+///
+/// Filesystems are synced and unmounted first, but processes are left
+/// running: init stops its services before calling this. Root only.
+///
+/// `REBOOT_CMD_PENDING` instead collects an action the kernel wants init
+/// to take (power button, critical temperature or battery), see
+/// [`crate::power::system::request`].
+///
+/// # Returns
+/// Only `REBOOT_CMD_SUSPEND` returns, with 0 once the machine has resumed,
+/// or `NotImplemented` unless booted with `suspend` on a platform with
+/// suspend to RAM (see [`crate::power::system`]). `REBOOT_CMD_PENDING`
+/// returns the command for the requested action, or 0 if there is none.
+pub fn sys_reboot(cmd: usize) -> SyscallResult {
+    use crate::power::system::PowerAction;
+
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+    let action = match cmd {
+        REBOOT_CMD_RESTART => PowerAction::Reboot,
+        REBOOT_CMD_POWER_OFF => PowerAction::PowerOff,
+        REBOOT_CMD_HALT => PowerAction::Halt,
+        REBOOT_CMD_SUSPEND => PowerAction::Suspend,
+        REBOOT_CMD_PENDING => {
+            return Ok(match crate::power::system::take_request() {
+                None => 0,
+                Some(PowerAction::Reboot) => REBOOT_CMD_RESTART,
+                Some(PowerAction::PowerOff) => REBOOT_CMD_POWER_OFF,
+                Some(PowerAction::Halt) => REBOOT_CMD_HALT,
+                Some(PowerAction::Suspend) => REBOOT_CMD_SUSPEND,
+            })
+        }
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+
+    crate::power::system::perform(action).map_err(|e| match e {
+        crate::error::KernelError::OperationNotSupported { .. } => SyscallError::NotImplemented,
+        _ => SyscallError::IoError,
+    })?;
+    Ok(0)
+}
+
+/// ABI version handshake.
+///
+/// # Arguments
+/// * `required` - The ABI version the caller was built against, as
+///   [`AbiVersion::to_raw`], or 0 to only query the kernel's version.
+///
+/// # Returns
+/// The kernel's ABI version as [`AbiVersion::to_raw`], or
+/// `OperationNotSupported` if this kernel cannot run a binary built against
+/// `required` (a different major version, or a newer minor version).
+pub fn sys_abi_version(required: usize) -> SyscallResult {
+    if required != 0 && !ABI_VERSION.supports(AbiVersion::from_raw(required)) {
+        return Err(SyscallError::OperationNotSupported);
+    }
+    Ok(ABI_VERSION.to_raw())
+}
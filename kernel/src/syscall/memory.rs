@@ -4,14 +4,24 @@
 //! - `sys_mmap` (20): Map memory (anonymous or file-backed)
 //! - `sys_munmap` (21): Unmap a memory region
 //! - `sys_mprotect` (22): Change page protection flags
+//! - `sys_heap_profile` (382): Register userland allocator statistics
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
-use super::{validate_user_pointer, SyscallError, SyscallResult};
+use super::{
+    uaccess::{copy_from_user, copy_to_user, user_range},
+    SyscallError, SyscallResult,
+};
 use crate::{
-    mm::{vas::MappingType, VirtualAddress, PAGE_SIZE},
-    process,
+    mm::{
+        vas::{MappingType, VirtualAddressSpace},
+        VirtualAddress, PAGE_SIZE,
+    },
+    process::{
+        self,
+        rlimit::{RLIMIT_AS, RLIMIT_DATA, RLIM_INFINITY},
+    },
 };
 
 // ============================================================================
@@ -39,6 +49,8 @@ pub const MAP_PRIVATE: usize = 0x02;
 pub const MAP_FIXED: usize = 0x10;
 /// The mapping is not backed by any file (zero-filled).
 pub const MAP_ANONYMOUS: usize = 0x20;
+/// Back an anonymous mapping with 2MB pages where possible (a hint).
+pub const MAP_HUGETLB: usize = 0x40000;
 
 /// Sentinel value indicating a failed mapping.
 pub const MAP_FAILED: usize = usize::MAX;
@@ -60,6 +72,40 @@ fn prot_to_mapping_type(prot: usize, shared: bool) -> MappingType {
     }
 }
 
+/// Enforce W^X for a user mapping request.
+///
+/// Writable+executable protections are only granted to processes exempted
+/// at exec time (binaries marked `PT_WXNEEDED`, i.e. JITs).
+fn check_wx(proc: &process::Process, prot: usize) -> Result<(), SyscallError> {
+    let exempt = proc.wx_exempt.load(core::sync::atomic::Ordering::Acquire);
+    if crate::security::memory_protection::user_mapping_allowed(
+        prot & PROT_WRITE != 0,
+        prot & PROT_EXEC != 0,
+        exempt,
+    ) {
+        Ok(())
+    } else {
+        Err(SyscallError::PermissionDenied)
+    }
+}
+
+/// Enforce `RLIMIT_AS` before growing `space` by `len` bytes.
+fn check_address_space(
+    proc: &process::Process,
+    space: &VirtualAddressSpace,
+    len: u64,
+) -> Result<(), SyscallError> {
+    let limit = proc.rlimits.soft(RLIMIT_AS);
+    if limit == RLIM_INFINITY {
+        return Ok(());
+    }
+    let mapped = space.get_stats().total_size as u64;
+    if mapped.saturating_add(len) > limit {
+        return Err(SyscallError::OutOfMemory);
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Syscall implementations
 // ============================================================================
@@ -75,7 +121,8 @@ fn prot_to_mapping_type(prot: usize, shared: bool) -> MappingType {
 /// - `length`: Size of the mapping in bytes (rounded up to page size).
 /// - `prot`: Protection flags (PROT_READ | PROT_WRITE | PROT_EXEC).
 /// - `flags`: Mapping flags (MAP_SHARED | MAP_PRIVATE | MAP_ANONYMOUS |
-///   MAP_FIXED).
+///   MAP_FIXED | MAP_HUGETLB). MAP_HUGETLB is honoured for private anonymous
+///   mappings at a kernel-chosen address and ignored otherwise.
 /// - `fd_offset`: Packed fd (upper 32 bits) and offset (lower 32 bits) for
 ///   file-backed mappings. Ignored for MAP_ANONYMOUS.
 ///
@@ -98,11 +145,6 @@ pub fn sys_mmap(
         return Err(SyscallError::InvalidArgument);
     }
 
-    // Enforce W^X: writable + executable is not allowed
-    if prot & PROT_WRITE != 0 && prot & PROT_EXEC != 0 {
-        return Err(SyscallError::PermissionDenied);
-    }
-
     // Must specify either SHARED or PRIVATE (not both, not neither)
     let shared = flags & MAP_SHARED != 0;
     let private = flags & MAP_PRIVATE != 0;
@@ -118,6 +160,9 @@ pub fn sys_mmap(
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
 
+    // Enforce W^X: writable + executable only for JIT-capable processes
+    check_wx(proc, prot)?;
+
     let is_anonymous = flags & MAP_ANONYMOUS != 0;
     let fd = if !is_anonymous { fd_offset >> 32 } else { 0 };
     let offset = if !is_anonymous {
@@ -128,6 +173,7 @@ pub fn sys_mmap(
 
     let mapping_type = prot_to_mapping_type(prot, shared);
     let memory_space = proc.memory_space.lock();
+    check_address_space(proc, &memory_space, length as u64)?;
 
     let mapped_addr = if is_fixed {
         // MAP_FIXED: map at the exact requested address
@@ -135,6 +181,14 @@ pub fn sys_mmap(
             .map_region(VirtualAddress(addr as u64), length, mapping_type)
             .map_err(|_| SyscallError::OutOfMemory)?;
         addr
+    } else if flags & MAP_HUGETLB != 0 && is_anonymous && !shared {
+        // Large anonymous mapping: try 2MB pages, fall back to 4KB pages
+        // when no aligned physical memory is free
+        let vaddr = memory_space
+            .mmap_huge(length, mapping_type)
+            .or_else(|_| memory_space.mmap(length, mapping_type))
+            .map_err(|_| SyscallError::OutOfMemory)?;
+        vaddr.as_usize()
     } else {
         // Kernel-chosen address: use VAS.mmap() which bumps next_mmap_addr
         let vaddr = memory_space
@@ -143,6 +197,14 @@ pub fn sys_mmap(
         vaddr.as_usize()
     };
 
+    // Code mappings default to read-only; a JIT's W+X request needs the
+    // writable bit as well
+    if prot & PROT_WRITE != 0 && prot & PROT_EXEC != 0 {
+        memory_space
+            .protect_region(VirtualAddress(mapped_addr as u64), length, prot)
+            .map_err(|_| SyscallError::InvalidArgument)?;
+    }
+
     // Register anonymous mappings with demand paging for COW and future
     // lazy allocation support. The pages are currently allocated eagerly by
     // VAS::mmap(), but registering them enables the page fault handler to
@@ -175,6 +237,9 @@ pub fn sys_mmap(
             let aligned_len = (length + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
             let mut buf = alloc::vec![0u8; aligned_len];
             let _bytes_read = file.node.read(offset, &mut buf).unwrap_or(0);
+            if let Some(path) = &file.path {
+                memory_space.set_backing(mapped_addr as u64, aligned_len, path);
+            }
 
             // Write file data into the mapped region via physical memory.
             // The pages are mapped in the process's page tables. We walk the
@@ -230,7 +295,7 @@ pub fn sys_munmap(addr: usize, length: usize) -> SyscallResult {
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
 
     // Validate the region is in user space
-    validate_user_pointer(addr, length)?;
+    user_range(addr, length)?;
 
     let memory_space = proc.memory_space.lock();
     let result = memory_space.unmap(addr, length);
@@ -269,12 +334,10 @@ pub fn sys_mprotect(addr: usize, length: usize, prot: usize) -> SyscallResult {
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
 
     // Validate the region is in user space
-    validate_user_pointer(addr, length)?;
+    user_range(addr, length)?;
 
     // Check W^X violation
-    if prot & PROT_WRITE != 0 && prot & PROT_EXEC != 0 {
-        return Err(SyscallError::PermissionDenied);
-    }
+    check_wx(proc, prot)?;
 
     // Verify the mapping exists in the process's address space
     let memory_space = proc.memory_space.lock();
@@ -319,12 +382,18 @@ pub fn sys_brk(addr: usize) -> SyscallResult {
     let new_break = if addr == 0 {
         None
     } else {
-        // Validate: reject requests that would exceed the max heap size.
+        // Validate: reject requests that would exceed the max heap size
+        // or the process's RLIMIT_DATA / RLIMIT_AS.
         let heap_start = memory_space.heap_start_addr();
         let requested = addr as u64;
-        if requested > heap_start + MAX_USER_HEAP_SIZE {
+        let current = memory_space.brk(None).as_u64();
+        let heap_limit = MAX_USER_HEAP_SIZE.min(proc.rlimits.soft(RLIMIT_DATA));
+        if requested > heap_start.saturating_add(heap_limit)
+            || (requested > current
+                && check_address_space(proc, &memory_space, requested - current).is_err())
+        {
             // Return current break (unchanged) to signal failure.
-            return Ok(memory_space.brk(None).as_usize());
+            return Ok(current as usize);
         }
 
         // Page-align the request upward for efficiency.
@@ -338,101 +407,114 @@ pub fn sys_brk(addr: usize) -> SyscallResult {
     Ok(result.as_usize())
 }
 
+/// Register the calling process's allocator statistics.
+///
+/// # Arguments
+/// - `addr`: User-space `struct veridian_heap_stats`, or 0 to unregister.
+/// - `len`: Its size, which must match the kernel's layout.
+///
+/// # Returns
+/// 0 on success. `/proc/<pid>/heap` reads the statistics from then on;
+/// exec forgets them, fork keeps them.
+pub fn sys_heap_profile(addr: usize, len: usize) -> SyscallResult {
+    if addr != 0 {
+        if len != core::mem::size_of::<veridian_abi::heap_profile::HeapStats>() {
+            return Err(SyscallError::InvalidArgument);
+        }
+        user_range(addr, len)?;
+    }
+    let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
+    proc.heap_stats_addr
+        .store(addr as u64, core::sync::atomic::Ordering::Release);
+    Ok(0)
+}
+
 // ============================================================================
 // Resource limits (POSIX getrlimit / setrlimit)
 // ============================================================================
 
-/// POSIX resource limit identifiers
-const RLIMIT_AS: usize = 9; // Address space size
-const RLIMIT_DATA: usize = 2; // Data segment size (heap)
-const RLIMIT_STACK: usize = 3; // Stack size
-const RLIMIT_NOFILE: usize = 7; // Max open files
-const RLIMIT_FSIZE: usize = 1; // Max file size
-
-/// RLIM_INFINITY -- unlimited
-const RLIM_INFINITY: u64 = u64::MAX;
-
 /// rlimit structure (matches POSIX)
 #[repr(C)]
+#[derive(Clone, Copy)]
 struct Rlimit {
     rlim_cur: u64, // soft limit
     rlim_max: u64, // hard limit
 }
 
-/// Get resource limits (syscall 260).
-///
-/// # Arguments
-/// - `resource`: RLIMIT_* constant
-/// - `rlim_ptr`: Pointer to user-space Rlimit struct to fill
-pub fn sys_getrlimit(resource: usize, rlim_ptr: usize) -> SyscallResult {
+/// Copy `proc`'s limits for `resource` to the user-space Rlimit at
+/// `rlim_ptr`.
+pub(super) fn get_rlimit(
+    proc: &process::Process,
+    resource: usize,
+    rlim_ptr: usize,
+) -> SyscallResult {
     if rlim_ptr == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_pointer(rlim_ptr, core::mem::size_of::<Rlimit>())?;
+    let (mut cur, mut max) = proc
+        .rlimits
+        .effective(resource)
+        .ok_or(SyscallError::InvalidArgument)?;
+    if resource == RLIMIT_DATA {
+        // brk never grows the heap past MAX_USER_HEAP_SIZE anyway
+        cur = cur.min(MAX_USER_HEAP_SIZE);
+        max = max.min(MAX_USER_HEAP_SIZE);
+    }
 
-    let (cur, max) = match resource {
-        RLIMIT_AS => {
-            let limit = MAX_USER_HEAP_SIZE;
-            (limit, limit)
-        }
-        RLIMIT_DATA => {
-            let limit = MAX_USER_HEAP_SIZE;
-            (limit, limit)
-        }
-        RLIMIT_STACK => {
-            let stack_size = 8 * 1024 * 1024u64; // 8MB default
-            (stack_size, stack_size)
-        }
-        RLIMIT_NOFILE => {
-            (256, 256) // current fd table limit
-        }
-        RLIMIT_FSIZE => {
-            (RLIM_INFINITY, RLIM_INFINITY) // no file size limit
-        }
-        _ => return Err(SyscallError::InvalidArgument),
+    let rlim = Rlimit {
+        rlim_cur: cur,
+        rlim_max: max,
     };
+    copy_to_user(rlim_ptr, &rlim)?;
 
-    // Write the rlimit struct to user space
-    let rlim = rlim_ptr as *mut Rlimit;
-    unsafe {
-        (*rlim).rlim_cur = cur;
-        (*rlim).rlim_max = max;
-    }
+    Ok(0)
+}
 
+/// Set `proc`'s limits for `resource` from the user-space Rlimit at
+/// `rlim_ptr`. Raising a hard limit requires the caller to be root.
+pub(super) fn set_rlimit(
+    proc: &process::Process,
+    resource: usize,
+    rlim_ptr: usize,
+) -> SyscallResult {
+    if rlim_ptr == 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let rlim: Rlimit = copy_from_user(rlim_ptr)?;
+    let (cur, max) = (rlim.rlim_cur, rlim.rlim_max);
+
+    let caller = process::current_process().ok_or(SyscallError::InvalidState)?;
+    proc.rlimits
+        .set(resource, cur, max, caller.euid() == 0)
+        .map_err(|e| match e {
+            crate::error::KernelError::PermissionDenied { .. } => SyscallError::PermissionDenied,
+            _ => SyscallError::InvalidArgument,
+        })?;
     Ok(0)
 }
 
+/// Get resource limits (syscall 260).
+///
+/// NOFILE and NPROC report the process's limits capped by the system-wide
+/// maxima in `crate::limits`.
+///
+/// # Arguments
+/// - `resource`: RLIMIT_* constant
+/// - `rlim_ptr`: Pointer to user-space Rlimit struct to fill
+pub fn sys_getrlimit(resource: usize, rlim_ptr: usize) -> SyscallResult {
+    let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
+    get_rlimit(proc, resource, rlim_ptr)
+}
+
 /// Set resource limits (syscall 261).
 ///
-/// Currently only validates and acknowledges -- actual enforcement is via
-/// the existing per-resource constants. Future: per-process configurable
-/// limits.
+/// Limits are per process and inherited on fork; see `process::rlimit` for
+/// where each one is enforced.
 ///
 /// # Arguments
 /// - `resource`: RLIMIT_* constant
 /// - `rlim_ptr`: Pointer to user-space Rlimit struct with new values
 pub fn sys_setrlimit(resource: usize, rlim_ptr: usize) -> SyscallResult {
-    if rlim_ptr == 0 {
-        return Err(SyscallError::InvalidArgument);
-    }
-    validate_user_pointer(rlim_ptr, core::mem::size_of::<Rlimit>())?;
-
-    let rlim = rlim_ptr as *const Rlimit;
-    let (cur, max) = unsafe { ((*rlim).rlim_cur, (*rlim).rlim_max) };
-
-    // Validate: soft limit must not exceed hard limit
-    if cur > max {
-        return Err(SyscallError::InvalidArgument);
-    }
-
-    // Validate the resource type is known
-    match resource {
-        RLIMIT_AS | RLIMIT_DATA | RLIMIT_STACK | RLIMIT_NOFILE | RLIMIT_FSIZE => {}
-        _ => return Err(SyscallError::InvalidArgument),
-    }
-
-    // Acknowledge the request (limits are currently global constants).
-    // Per-process configurable limits deferred to later sprint.
-    let _ = (cur, max);
-    Ok(0)
+    let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
+    set_rlimit(proc, resource, rlim_ptr)
 }
@@ -3,25 +3,12 @@
 //! Provides the kernel-side implementation of system calls including IPC
 //! operations.
 //!
-//! # User-Space Pointer Validation Contract
+//! # User Memory Access
 //!
-//! Every syscall handler that accepts a user-space pointer **must** call
-//! [`validate_user_pointer`] (or the typed [`validate_user_buffer`]) before
-//! dereferencing it. The validation enforces:
-//!
-//! 1. **Non-null** -- the pointer is not zero.
-//! 2. **User-space range** -- the entire `[ptr, ptr+size)` region falls within
-//!    the architecture-specific user-space address range (below
-//!    [`USER_SPACE_END`]).
-//! 3. **No arithmetic overflow** -- `ptr + size` does not wrap around.
-//! 4. **Size cap** -- the buffer size does not exceed [`MAX_BUFFER_SIZE`] (256
-//!    MB).
-//! 5. **Alignment** -- for typed access via [`validate_user_ptr_typed`], the
-//!    pointer is suitably aligned for `T`.
-//!
-//! Handlers that read null-terminated strings from user space must still pass
-//! the base pointer through validation with a minimum size of 1 before
-//! beginning the byte-by-byte scan.
+//! Handlers never dereference user-space pointers. Every read or write of
+//! user memory goes through [`uaccess`], whose copy primitives check the
+//! range against the caller's address space and turn a fault during the copy
+//! into [`SyscallError::InvalidPointer`] instead of a kernel crash.
 
 // System call handlers are fully implemented but not all are reachable
 // from user-space yet. Will be exercised once SYSCALL/SYSRET transitions
@@ -30,84 +17,21 @@
 
 use core::sync::atomic::{AtomicU64, Ordering};
 
+use errno::Errno;
+/// System call numbers, generated from the table in `veridian-abi`.
+pub use veridian_abi::Syscall;
+
+use self::uaccess::{
+    access_ok, copy_bytes_to_user, copy_from_user, copy_slice_from_user, strncpy_from_user,
+    user_range, Access,
+};
+#[cfg(feature = "net")]
+use self::uaccess::{copy_bytes_from_user, copy_slice_to_user};
 use crate::{
     ipc::{sync_call, sync_receive, sync_reply, sync_send, IpcError, Message, SmallMessage},
     sched,
 };
 
-/// Maximum valid user-space address.
-///
-/// On x86_64 this is the canonical upper bound of user space (128 TB).
-/// AArch64 and RISC-V use the same logical split for the QEMU virt machine.
-const USER_SPACE_END: usize = 0x0000_7FFF_FFFF_FFFF;
-
-/// Maximum allowed buffer size for syscall arguments (256 MB).
-const MAX_BUFFER_SIZE: usize = 256 * 1024 * 1024;
-
-/// Validate that a user-space pointer and size are within bounds.
-///
-/// Checks:
-/// - `ptr` is non-null
-/// - `size` does not exceed [`MAX_BUFFER_SIZE`]
-/// - `ptr + size` does not overflow
-/// - the entire range `[ptr, ptr+size)` is below [`USER_SPACE_END`]
-#[inline]
-fn validate_user_pointer(ptr: usize, size: usize) -> Result<(), SyscallError> {
-    if ptr == 0 {
-        return Err(SyscallError::InvalidPointer);
-    }
-    if size > MAX_BUFFER_SIZE {
-        return Err(SyscallError::InvalidArgument);
-    }
-    // Check for overflow and that the entire range is in user space
-    let end = ptr.checked_add(size).ok_or(SyscallError::InvalidPointer)?;
-    if end > USER_SPACE_END {
-        return Err(SyscallError::AccessDenied);
-    }
-    Ok(())
-}
-
-/// Validate a user-space buffer of `len` bytes starting at `ptr`.
-///
-/// This is the canonical entry point for all syscall handlers that accept
-/// user-space memory regions. It combines null, range, overflow, and size
-/// checks in a single call.
-///
-/// # Errors
-///
-/// - [`SyscallError::InvalidPointer`] if `ptr` is null or overflows.
-/// - [`SyscallError::InvalidArgument`] if `len` exceeds [`MAX_BUFFER_SIZE`].
-/// - [`SyscallError::AccessDenied`] if the range extends into kernel space.
-#[inline]
-pub(crate) fn validate_user_buffer(ptr: usize, len: usize) -> Result<(), SyscallError> {
-    validate_user_pointer(ptr, len)
-}
-
-/// Validate a user-space pointer for a typed access of `T`.
-///
-/// In addition to the range checks performed by [`validate_user_pointer`],
-/// this verifies that `ptr` is aligned to `core::mem::align_of::<T>()`.
-#[inline]
-pub(crate) fn validate_user_ptr_typed<T>(ptr: usize) -> Result<(), SyscallError> {
-    let size = core::mem::size_of::<T>();
-    validate_user_pointer(ptr, size)?;
-    let align = core::mem::align_of::<T>();
-    if !ptr.is_multiple_of(align) {
-        return Err(SyscallError::InvalidPointer);
-    }
-    Ok(())
-}
-
-/// Validate a user-space pointer for a null-terminated string read.
-///
-/// Checks that `ptr` is non-null and that at least the first byte falls
-/// within user-space. Callers must additionally re-validate on each page
-/// crossing during the byte-by-byte scan.
-#[inline]
-fn validate_user_string_ptr(ptr: usize) -> Result<(), SyscallError> {
-    validate_user_pointer(ptr, 1)
-}
-
 /// Syscall rate limiter using token bucket algorithm
 struct SyscallRateLimiter {
     /// Tokens available (scaled by 1000 for precision)
@@ -191,20 +115,33 @@ use self::debug::*;
 mod memory;
 use self::memory::*;
 
+// Import capability inspection / audit syscalls module
+mod security;
+use self::security::*;
+
+// Import cryptographic primitive syscalls module
+mod crypto;
+use self::crypto::*;
+
 // Import user space utilities
 mod arch_prctl;
 mod futex;
 mod thread_clone;
+pub(crate) mod uaccess;
 mod userspace;
 pub use futex::sys_futex_wake;
-pub use userspace::copy_to_user;
+pub use uaccess::copy_to_user;
 
 // Import Phase 6 syscall modules
 mod graphics_syscalls;
 use self::graphics_syscalls::*;
+#[cfg(feature = "desktop")]
 mod wayland_syscalls;
+#[cfg(feature = "desktop")]
 use self::wayland_syscalls::*;
+#[cfg(feature = "net")]
 mod network_ext_syscalls;
+#[cfg(feature = "net")]
 use self::network_ext_syscalls::*;
 
 // Phase 7.5 Wave 8: Shell/Userland extensions (io_uring, ptrace, core dump,
@@ -216,354 +153,147 @@ mod pty;
 #[allow(unused_imports)]
 use self::pty::{sys_grantpt, sys_openpty, sys_ptsname, sys_unlockpt};
 
-/// System call numbers
-#[repr(usize)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Syscall {
-    // IPC system calls
-    IpcSend = 0,
-    IpcReceive = 1,
-    IpcCall = 2,
-    IpcReply = 3,
-    IpcCreateEndpoint = 4,
-    IpcBindEndpoint = 5,
-    IpcShareMemory = 6,
-    IpcMapMemory = 7,
-
-    // Process management
-    ProcessYield = 10,
-    ProcessExit = 11,
-    ProcessFork = 12,
-    ProcessExec = 13,
-    ProcessWait = 14,
-    ProcessGetPid = 15,
-    ProcessGetPPid = 16,
-    ProcessSetPriority = 17,
-    ProcessGetPriority = 18,
-
-    // Thread management
-    ThreadCreate = 40,
-    ThreadExit = 41,
-    ThreadJoin = 42,
-    ThreadGetTid = 43,
-    ThreadSetAffinity = 44,
-    ThreadGetAffinity = 45,
-    ThreadClone = 46,
-
-    // Memory management
-    MemoryMap = 20,
-    MemoryUnmap = 21,
-    MemoryProtect = 22,
-    MemoryBrk = 23,
-
-    // Capability management
-    CapabilityGrant = 30,
-    CapabilityRevoke = 31,
-
-    // Filesystem operations
-    FileOpen = 50,
-    FileClose = 51,
-    FileRead = 52,
-    FileWrite = 53,
-    FileSeek = 54,
-    FileStat = 55,
-    FileTruncate = 56,
-
-    // Directory operations
-    DirMkdir = 60,
-    DirRmdir = 61,
-    DirOpendir = 62,
-    DirReaddir = 63,
-    DirClosedir = 64,
-    FilePipe2 = 65,
-    FileDup3 = 66,
-
-    // Filesystem management
-    FsMount = 70,
-    FsUnmount = 71,
-    FsSync = 72,
-    FsFsync = 73,
-
-    // Kernel information
-    KernelGetInfo = 80,
-
-    // Package management
-    PkgInstall = 90,
-    PkgRemove = 91,
-    PkgQuery = 92,
-    PkgList = 93,
-    PkgUpdate = 94,
-
-    // Extended filesystem operations
-    FileDup = 57,
-    FileDup2 = 58,
-    FilePipe = 59,
-
-    // Time management
-    TimeGetUptime = 100,
-    TimeCreateTimer = 101,
-    TimeCancelTimer = 102,
-
-    // Extended process operations
-    ProcessGetcwd = 110,
-    ProcessChdir = 111,
-    FileIoctl = 112,
-    ProcessKill = 113,
-
-    // Signal management
-    SigAction = 120,
-    SigProcmask = 121,
-    SigSuspend = 122,
-    SigReturn = 123,
-
-    // POSIX time syscalls
-    ClockGettime = 160,
-    ClockGetres = 161,
-    Nanosleep = 162,
-    Gettimeofday = 163,
-
-    // Identity syscalls
-    Getuid = 170,
-    Geteuid = 171,
-    Getgid = 172,
-    Getegid = 173,
-    Setuid = 174,
-    Setgid = 175,
-
-    // Process group / session syscalls
-    Setpgid = 176,
-    Getpgid = 177,
-    Getpgrp = 178,
-    Setsid = 179,
-    Getsid = 180,
-
-    // Scatter/gather I/O
-    Readv = 183,
-    Writev = 184,
-
-    // Debug / tracing
-    Ptrace = 140,
-
-    // Extended filesystem operations (Phase 4B)
-    FileStatPath = 150,
-    FileLstat = 151,
-    FileReadlink = 152,
-    FileAccess = 153,
-    FileRename = 154,
-    FileLink = 155,
-    FileSymlink = 156,
-    FileUnlink = 157,
-    FileFcntl = 158,
-
-    // New filesystem ops for self-hosting (Phase 4A)
-    FileChmod = 185,
-    FileFchmod = 186,
-    ProcessUmask = 187,
-    FileTruncatePath = 188,
-    FilePoll = 189,
-    FileOpenat = 190,
-    FileFstatat = 191,
-    FileUnlinkat = 192,
-    FileMkdirat = 193,
-    FileRenameat = 194,
-    FilePread = 195,
-    FilePwrite = 196,
-
-    // Ownership and device node syscalls
-    FileChown = 197,
-    FileFchown = 198,
-    FileMknod = 199,
-    FileSelect = 200,
-    FutexWait = 201,
-    FutexWake = 202,
-    ArchPrctl = 203,
-
-    // System information
-    ProcessUname = 204,
-    /// Look up an environment variable by name from the process's env_vars.
-    ///
-    /// Required because some CRT implementations (e.g. GCC's internal CRT)
-    /// skip __libc_start_main, leaving the libc `environ` pointer NULL.
-    ProcessGetenv = 205,
-
-    // POSIX shared memory
-    ShmOpen = 210,
-    ShmUnlink = 211,
-    ShmTruncate = 212,
-
-    // Socket operations
-    SocketCreate = 220,
-    SocketBind = 221,
-    SocketListen = 222,
-    SocketConnect = 223,
-    SocketAccept = 224,
-    SocketSend = 225,
-    SocketRecv = 226,
-    SocketClose = 227,
-    SocketPair = 228,
-
-    // Graphics / framebuffer (Phase 6)
-    FbGetInfo = 230,
-    FbMap = 231,
-    InputPoll = 232,
-    InputRead = 233,
-    FbSwap = 234,
-
-    // Wayland compositor (Phase 6)
-    WlConnect = 240,
-    WlDisconnect = 241,
-    WlSendMessage = 242,
-    WlRecvMessage = 243,
-    WlCreateShmPool = 244,
-    WlCreateSurface = 245,
-    WlCommitSurface = 246,
-    WlGetEvents = 247,
-
-    // Network (Phase 6) -- AF_INET extensions
-    NetSendTo = 250,
-    NetRecvFrom = 251,
-    NetGetSockName = 252,
-    NetGetPeerName = 253,
-    NetSetSockOpt = 254,
-    NetGetSockOpt = 255,
-
-    // Resource limits (Phase 6.5)
-    GetRlimit = 260,
-    SetRlimit = 261,
-
-    // epoll I/O multiplexing (Phase 6.5)
-    EpollCreate = 262,
-    EpollCtl = 263,
-    EpollWait = 264,
-
-    // Process groups / sessions (Phase 6.5)
-    SetPgid = 270,
-    GetPgid = 271,
-    SetSid = 272,
-    GetSid = 273,
-    TcSetPgrp = 274,
-    TcGetPgrp = 275,
-
-    // PTY (Phase 6.5)
-    OpenPty = 280,
-    GrantPty = 281,
-    UnlockPty = 282,
-    PtsName = 283,
-
-    // Filesystem extensions (Phase 6.5)
-    Link = 290,
-    Symlink = 291,
-    Readlink = 292,
-    Lstat = 293,
-    Fchmod = 294,
-    Fchown = 295,
-    Umask = 296,
-    Access = 297,
-
-    // Poll/fcntl (Phase 6.5)
-    Poll = 300,
-    Fcntl = 301,
-
-    // Threading (Phase 6.5)
-    Clone = 310,
-    Futex = 311,
-
-    // Audio (Phase 7)
-    AudioOpen = 320,
-    AudioClose = 321,
-    AudioWrite = 322,
-    AudioSetVolume = 323,
-    AudioGetInfo = 324,
-    AudioStart = 325,
-    AudioStop = 326,
-    AudioPause = 327,
-
-    // musl libc compatibility syscalls
-    Getdents64 = 340,
-    Prlimit64 = 341,
-    InotifyInit1 = 342,
-    InotifyAddWatch = 343,
-    InotifyRmWatch = 344,
-    Madvise = 345,
-
-    // *at() syscalls for musl (dirfd-relative path operations)
-    Fchmodat = 346,
-    Fchownat = 347,
-    Linkat = 348,
-    Symlinkat = 349,
-    Readlinkat = 350,
-    MemfdCreate = 351,
-    SetTidAddress = 352,
-    SetRobustList = 353,
-    ClockNanosleep = 354,
-
-    // Event/timer notification fds (KDE/Wayland infrastructure)
-    Getrandom = 330,
-    EventfdCreate = 331,
-    EventfdRead = 332,
-    EventfdWrite = 333,
-    TimerfdCreate = 334,
-    TimerfdSettime = 335,
-    TimerfdGettime = 336,
-    SignalfdCreate = 337,
-    SendMsg = 338,
-    RecvMsg = 339,
-}
-
 /// System call result type
 pub type SyscallResult = Result<usize, SyscallError>;
 
-/// System call error codes
-#[repr(i32)]
+/// System call error codes.
+///
+/// A failing syscall returns `-errno` with the number from [`Self::errno`];
+/// the numbering is shared with user space through the `errno` crate and
+/// `<veridian/errno.h>`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SyscallError {
-    InvalidSyscall = -1,
-    InvalidArgument = -2,
-    PermissionDenied = -3,
-    ResourceNotFound = -4,
-    OutOfMemory = -5,
-    WouldBlock = -6,
-    Interrupted = -7,
-    InvalidState = -8,
-    InvalidPointer = -9,
+    /// No such syscall (ENOSYS).
+    InvalidSyscall,
+    InvalidArgument,
+    PermissionDenied,
+    ResourceNotFound,
+    OutOfMemory,
+    WouldBlock,
+    Interrupted,
+    /// The object is not in a state that allows the operation (EILSEQ).
+    InvalidState,
+    InvalidPointer,
 
     // Capability-specific errors
-    InvalidCapability = -10,
-    CapabilityRevoked = -11,
-    InsufficientRights = -12,
-    CapabilityNotFound = -13,
-    CapabilityAlreadyExists = -14,
-    InvalidCapabilityObject = -15,
-    CapabilityDelegationDenied = -16,
+    InvalidCapability,
+    CapabilityRevoked,
+    InsufficientRights,
+    CapabilityNotFound,
+    CapabilityAlreadyExists,
+    InvalidCapabilityObject,
+    CapabilityDelegationDenied,
 
     // Memory validation errors
-    UnmappedMemory = -17,
-    AccessDenied = -18,
-    ProcessNotFound = -19,
+    UnmappedMemory,
+    AccessDenied,
+    ProcessNotFound,
 
-    // Filesystem errors (values match veridian/errno.h)
-    FileExists = -20,
-    BadFileDescriptor = -21,
-    IoError = -22,
+    // Filesystem errors
+    FileExists,
+    BadFileDescriptor,
+    IoError,
+
+    /// No such device or address (ENXIO); e.g. SEEK_DATA past the last data.
+    NoSuchAddress,
 
     // Exec errors
-    ArgumentListTooLong = -24,
-
-    NotADirectory = -28,
-    IsADirectory = -29,
-    NotATerminal = -32,
-    BrokenPipe = -39,
-    DirectoryNotEmpty = -45,
-    /// Resource limit exceeded (process table full, fd table full, etc.)
-    /// Maps to ERESOURCELIMIT (errno 79) in user space.
-    /// For POSIX fork() EAGAIN semantics, prefer WouldBlock (errno 6).
-    ResourceLimitExceeded = -79,
+    ArgumentListTooLong,
+
+    NotADirectory,
+    IsADirectory,
+    /// Descriptor limit (RLIMIT_NOFILE) reached (EMFILE).
+    TooManyOpenFiles,
+    NotATerminal,
+    /// File too large (EFBIG).
+    FileTooLarge,
+    /// No space left on device (ENOSPC).
+    NoSpace,
+    /// Device or resource busy (EBUSY).
+    Busy,
+    /// Read-only file system (EROFS).
+    ReadOnlyFs,
+    BrokenPipe,
+    /// Result does not fit the caller's buffer (ERANGE).
+    OutOfRange,
+    /// File name or path component too long (ENAMETOOLONG).
+    NameTooLong,
+    /// Operation not supported by this file or filesystem (ENOTSUP).
+    OperationNotSupported,
+    /// Socket operation on a descriptor that is not a socket (ENOTSOCK).
+    NotASocket,
+    DirectoryNotEmpty,
+    /// Link or rename across filesystems (EXDEV).
+    CrossDevice,
+    /// Operation timed out (ETIMEDOUT).
+    TimedOut,
+    /// Resource limit exceeded (process table full, etc.), ERESOURCELIMIT.
+    /// For POSIX fork() EAGAIN semantics, prefer WouldBlock.
+    ResourceLimitExceeded,
     /// Syscall registered but not yet implemented (Phase 6.5 stubs).
-    /// Maps to ENOSYS (errno 38) in user space.
-    NotImplemented = -38,
+    /// Reported as ENOSYS, like an unknown syscall.
+    NotImplemented,
     /// Too many levels of symbolic links (ELOOP).
-    /// Maps to ELOOP (errno 40) in user space.
-    SymlinkLoop = -40,
+    SymlinkLoop,
+    /// Extended attribute does not exist (ENODATA).
+    NoData,
+}
+
+impl SyscallError {
+    /// The error number user space sees.
+    pub const fn errno(self) -> Errno {
+        match self {
+            SyscallError::InvalidSyscall | SyscallError::NotImplemented => Errno::ENOSYS,
+            SyscallError::InvalidArgument => Errno::EINVAL,
+            SyscallError::PermissionDenied => Errno::EPERM,
+            SyscallError::ResourceNotFound => Errno::ENOENT,
+            SyscallError::OutOfMemory => Errno::ENOMEM,
+            SyscallError::WouldBlock => Errno::EAGAIN,
+            SyscallError::Interrupted => Errno::EINTR,
+            SyscallError::InvalidState => Errno::EILSEQ,
+            SyscallError::InvalidPointer => Errno::EFAULT,
+            SyscallError::InvalidCapability => Errno::ECAPINVAL,
+            SyscallError::CapabilityRevoked => Errno::ECAPREVOKED,
+            SyscallError::InsufficientRights => Errno::ECAPRIGHTS,
+            SyscallError::CapabilityNotFound => Errno::ECAPNOTFOUND,
+            SyscallError::CapabilityAlreadyExists => Errno::ECAPEXISTS,
+            SyscallError::InvalidCapabilityObject => Errno::ECAPOBJECT,
+            SyscallError::CapabilityDelegationDenied => Errno::ECAPDELEG,
+            SyscallError::UnmappedMemory => Errno::ENOMAPPING,
+            SyscallError::AccessDenied => Errno::EACCES,
+            SyscallError::ProcessNotFound => Errno::ESRCH,
+            SyscallError::FileExists => Errno::EEXIST,
+            SyscallError::BadFileDescriptor => Errno::EBADF,
+            SyscallError::IoError => Errno::EIO,
+            SyscallError::NoSuchAddress => Errno::ENXIO,
+            SyscallError::ArgumentListTooLong => Errno::E2BIG,
+            SyscallError::NotADirectory => Errno::ENOTDIR,
+            SyscallError::IsADirectory => Errno::EISDIR,
+            SyscallError::TooManyOpenFiles => Errno::EMFILE,
+            SyscallError::NotATerminal => Errno::ENOTTY,
+            SyscallError::FileTooLarge => Errno::EFBIG,
+            SyscallError::NoSpace => Errno::ENOSPC,
+            SyscallError::Busy => Errno::EBUSY,
+            SyscallError::ReadOnlyFs => Errno::EROFS,
+            SyscallError::BrokenPipe => Errno::EPIPE,
+            SyscallError::OutOfRange => Errno::ERANGE,
+            SyscallError::NameTooLong => Errno::ENAMETOOLONG,
+            SyscallError::OperationNotSupported => Errno::ENOTSUP,
+            SyscallError::NotASocket => Errno::ENOTSOCK,
+            SyscallError::DirectoryNotEmpty => Errno::ENOTEMPTY,
+            SyscallError::CrossDevice => Errno::EXDEV,
+            SyscallError::TimedOut => Errno::ETIMEDOUT,
+            SyscallError::ResourceLimitExceeded => Errno::ERESOURCELIMIT,
+            SyscallError::SymlinkLoop => Errno::ELOOP,
+            SyscallError::NoData => Errno::ENODATA,
+        }
+    }
+}
+
+impl From<crate::error::KernelError> for SyscallError {
+    fn from(err: crate::error::KernelError) -> Self {
+        map_kernel_error(err)
+    }
 }
 
 impl From<IpcError> for SyscallError {
@@ -599,34 +329,96 @@ impl From<crate::cap::manager::CapError> for SyscallError {
     }
 }
 
-/// Map a KernelError (especially filesystem errors) to the appropriate
-/// SyscallError. Values match veridian/errno.h so libc's `__syscall_ret()` sets
-/// correct errno.
+impl From<crate::process::creds::CredError> for SyscallError {
+    fn from(err: crate::process::creds::CredError) -> Self {
+        match err {
+            crate::process::creds::CredError::NotPermitted => SyscallError::PermissionDenied,
+            crate::process::creds::CredError::InvalidId
+            | crate::process::creds::CredError::TooManyGroups => SyscallError::InvalidArgument,
+        }
+    }
+}
+
+/// Map a KernelError to the SyscallError (and so the errno) user space sees.
+///
+/// Handlers that need a different code for one case (e.g. ENODATA for a
+/// missing extended attribute) match that case first and fall back to this.
 pub fn map_kernel_error(err: crate::error::KernelError) -> SyscallError {
-    use crate::error::{FsError, KernelError};
+    use crate::error::{FsError, IpcError, KernelError, SchedError};
     match err {
         KernelError::FsError(fs_err) => match fs_err {
-            FsError::NotFound => SyscallError::ResourceNotFound,
+            FsError::NotFound | FsError::NoRootFs => SyscallError::ResourceNotFound,
             FsError::AlreadyExists => SyscallError::FileExists,
             FsError::PermissionDenied => SyscallError::PermissionDenied,
             FsError::NotADirectory => SyscallError::NotADirectory,
             FsError::IsADirectory => SyscallError::IsADirectory,
             FsError::DirectoryNotEmpty => SyscallError::DirectoryNotEmpty,
             FsError::BadFileDescriptor => SyscallError::BadFileDescriptor,
-            FsError::IoError => SyscallError::IoError,
-            FsError::NotAFile => SyscallError::InvalidArgument,
-            FsError::ReadOnly => SyscallError::PermissionDenied,
-            FsError::InvalidPath => SyscallError::InvalidArgument,
-            FsError::NoRootFs => SyscallError::ResourceNotFound,
-            FsError::TooManyOpenFiles => SyscallError::OutOfMemory,
-            _ => SyscallError::InvalidState,
+            FsError::IoError | FsError::CorruptedData => SyscallError::IoError,
+            FsError::NotAFile | FsError::NotASymlink | FsError::InvalidPath => {
+                SyscallError::InvalidArgument
+            }
+            FsError::ReadOnly => SyscallError::ReadOnlyFs,
+            FsError::AlreadyMounted => SyscallError::Busy,
+            FsError::NotMounted | FsError::UnknownFsType => SyscallError::InvalidArgument,
+            FsError::TooManyOpenFiles => SyscallError::TooManyOpenFiles,
+            FsError::NotSupported => SyscallError::OperationNotSupported,
+            FsError::FileTooLarge => SyscallError::FileTooLarge,
+            FsError::SymlinkLoop => SyscallError::SymlinkLoop,
+            FsError::NoSpace => SyscallError::NoSpace,
+            FsError::CrossDevice => SyscallError::CrossDevice,
         },
         KernelError::OutOfMemory { .. } => SyscallError::OutOfMemory,
+        KernelError::InvalidAddress { .. } => SyscallError::InvalidPointer,
+        KernelError::UnmappedMemory { .. } => SyscallError::UnmappedMemory,
+        KernelError::InvalidCapability { .. } => SyscallError::InvalidCapability,
+        KernelError::InsufficientRights { .. } => SyscallError::InsufficientRights,
+        KernelError::CapabilityRevoked { .. } => SyscallError::CapabilityRevoked,
+        KernelError::ProcessNotFound { .. } | KernelError::ThreadNotFound { .. } => {
+            SyscallError::ProcessNotFound
+        }
+        KernelError::InvalidState { .. } | KernelError::NotInitialized { .. } => {
+            SyscallError::InvalidState
+        }
+        KernelError::IpcError(ipc_err) => match ipc_err {
+            IpcError::InvalidEndpoint { .. } | IpcError::InvalidChannel { .. } => {
+                SyscallError::InvalidArgument
+            }
+            IpcError::MessageTooLarge { .. } => SyscallError::OutOfRange,
+            IpcError::QueueFull { .. } | IpcError::QueueEmpty | IpcError::WouldBlock => {
+                SyscallError::WouldBlock
+            }
+            IpcError::InvalidCapability => SyscallError::InvalidCapability,
+            IpcError::ProcessNotFound { .. } => SyscallError::ProcessNotFound,
+            IpcError::EndpointNotFound { .. } => SyscallError::ResourceNotFound,
+            IpcError::PermissionDenied => SyscallError::PermissionDenied,
+            IpcError::Timeout => SyscallError::TimedOut,
+        },
+        KernelError::SchedulerError(sched_err) => match sched_err {
+            SchedError::TaskNotFound { .. } => SyscallError::ProcessNotFound,
+            SchedError::AlreadyScheduled => SyscallError::Busy,
+            _ => SyscallError::InvalidArgument,
+        },
+        KernelError::SyscallError(sys_err) => match sys_err {
+            crate::error::SyscallError::InvalidSyscall { .. }
+            | crate::error::SyscallError::NotImplemented => SyscallError::InvalidSyscall,
+            crate::error::SyscallError::InvalidPointer { .. } => SyscallError::InvalidPointer,
+            crate::error::SyscallError::BufferTooSmall { .. } => SyscallError::OutOfRange,
+            crate::error::SyscallError::StringTooLong { .. } => SyscallError::NameTooLong,
+            crate::error::SyscallError::AccessDenied => SyscallError::AccessDenied,
+            crate::error::SyscallError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+        },
+        KernelError::HardwareError { .. } => SyscallError::IoError,
+        KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+        KernelError::OperationNotSupported { .. } => SyscallError::OperationNotSupported,
+        KernelError::ResourceExhausted { .. } => SyscallError::ResourceLimitExceeded,
         KernelError::PermissionDenied { .. } => SyscallError::PermissionDenied,
         KernelError::AlreadyExists { .. } => SyscallError::FileExists,
         KernelError::NotFound { .. } => SyscallError::ResourceNotFound,
+        KernelError::Timeout { .. } => SyscallError::TimedOut,
+        KernelError::NotImplemented { .. } => SyscallError::NotImplemented,
+        KernelError::WouldBlock => SyscallError::WouldBlock,
         KernelError::BrokenPipe => SyscallError::BrokenPipe,
-        _ => SyscallError::InvalidState,
     }
 }
 
@@ -661,7 +453,7 @@ pub extern "C" fn syscall_handler(
     // Rate limiting check
     if !SYSCALL_RATE_LIMITER.check() {
         SYSCALL_ERRORS.fetch_add(1, Ordering::Relaxed);
-        return SyscallError::WouldBlock as i32 as isize;
+        return SyscallError::WouldBlock.errno().to_ret();
     }
 
     // Get caller PID for audit logging
@@ -669,9 +461,14 @@ pub extern "C" fn syscall_handler(
         .map(|p| p.pid.0)
         .unwrap_or(0);
 
-    let result = match Syscall::try_from(syscall_num) {
-        Ok(syscall) => handle_syscall(syscall, arg1, arg2, arg3, arg4, arg5),
-        Err(_) => Err(SyscallError::InvalidSyscall),
+    // Syscall filters decide before anything looks at the arguments
+    let result = if crate::security::syscall_filter::check(syscall_num).is_err() {
+        Err(SyscallError::PermissionDenied)
+    } else {
+        match Syscall::try_from(syscall_num) {
+            Ok(syscall) => handle_syscall(syscall, arg1, arg2, arg3, arg4, arg5),
+            Err(_) => Err(SyscallError::InvalidSyscall),
+        }
     };
 
     // Audit log: syscall with result.
@@ -689,7 +486,7 @@ pub extern "C" fn syscall_handler(
 
     let ret = match result {
         Ok(value) => value as isize,
-        Err(error) => error as i32 as isize,
+        Err(error) => error.errno().to_ret(),
     };
 
     // Trace: syscall exit
@@ -726,6 +523,10 @@ fn handle_syscall(
         Syscall::IpcBindEndpoint => sys_ipc_bind_endpoint(arg1, arg2),
         Syscall::IpcShareMemory => sys_ipc_share_memory(arg1, arg2, arg3, arg4),
         Syscall::IpcMapMemory => sys_ipc_map_memory(arg1, arg2, arg3),
+        Syscall::IpcLookupEndpoint => sys_ipc_lookup_endpoint(arg1),
+        Syscall::IpcNamespaceCreate => sys_ipc_namespace_create(),
+        Syscall::IpcNamespaceAssign => sys_ipc_namespace_assign(arg1, arg2),
+        Syscall::IpcNamespaceExport => sys_ipc_namespace_export(arg1, arg2, arg3),
 
         // Process management
         Syscall::ProcessYield => sys_yield(),
@@ -765,6 +566,12 @@ fn handle_syscall(
         Syscall::MemoryProtect => sys_mprotect(arg1, arg2, arg3),
         Syscall::MemoryBrk => sys_brk(arg1),
 
+        // Capability inspection and audit
+        Syscall::CapabilityGrant => Err(SyscallError::NotImplemented),
+        Syscall::CapabilityRevoke => sys_cap_release(arg1),
+        Syscall::CapabilityInspect => sys_cap_inspect(arg1, arg2, arg3),
+        Syscall::AuditRead => sys_audit_read(arg1, arg2, arg3),
+
         // Directory operations
         Syscall::DirMkdir => sys_mkdir(arg1, arg2),
         Syscall::DirRmdir => sys_rmdir(arg1),
@@ -782,13 +589,29 @@ fn handle_syscall(
 
         // Kernel information
         Syscall::KernelGetInfo => sys_get_kernel_info(arg1),
+        Syscall::Dmesg => sys_dmesg(arg1, arg2, arg3),
+        Syscall::TraceCtl => sys_trace_ctl(arg1, arg2, arg3),
+        Syscall::CrashDump => sys_crash_dump(arg1, arg2, arg3),
+        Syscall::Watchdog => sys_watchdog(arg1, arg2),
+        Syscall::Reboot => sys_reboot(arg1),
+        Syscall::AbiVersion => sys_abi_version(arg1),
 
         // Package management
+        #[cfg(feature = "pkg")]
         Syscall::PkgInstall => sys_pkg_install(arg1, arg2),
+        #[cfg(feature = "pkg")]
         Syscall::PkgRemove => sys_pkg_remove(arg1, arg2),
+        #[cfg(feature = "pkg")]
         Syscall::PkgQuery => sys_pkg_query(arg1, arg2),
+        #[cfg(feature = "pkg")]
         Syscall::PkgList => sys_pkg_list(arg1, arg2),
+        #[cfg(feature = "pkg")]
         Syscall::PkgUpdate => sys_pkg_update(arg1),
+        #[cfg(feature = "pkg")]
+        Syscall::PkgVerify => sys_pkg_verify(arg1, arg2),
+        Syscall::BootSlotInstall => sys_boot_slot_install(arg1, arg2),
+        Syscall::BootSlotCommit => sys_boot_slot_commit(),
+        Syscall::BootSlotQuery => sys_boot_slot_query(arg1),
 
         // Extended process operations
         Syscall::ProcessGetcwd => sys_getcwd(arg1, arg2),
@@ -828,6 +651,10 @@ fn handle_syscall(
         Syscall::Setsid => sys_setsid(),
         Syscall::Getsid => sys_getsid(arg1),
 
+        // Supplementary groups
+        Syscall::Getgroups => sys_getgroups(arg1, arg2),
+        Syscall::Setgroups => sys_setgroups(arg1, arg2),
+
         // Scatter/gather I/O
         Syscall::Readv => sys_readv(arg1, arg2, arg3),
         Syscall::Writev => sys_writev(arg1, arg2, arg3),
@@ -876,20 +703,31 @@ fn handle_syscall(
         Syscall::ShmOpen => sys_shm_open(arg1, arg2, arg3),
         Syscall::ShmUnlink => sys_shm_unlink(arg1, arg2),
         Syscall::ShmTruncate => sys_shm_truncate(arg1, arg2, arg3),
+        Syscall::ShmClose => sys_shm_close(arg1, arg2),
 
         // Socket operations
+        #[cfg(feature = "net")]
         Syscall::SocketCreate => sys_socket_create(arg1, arg2),
+        #[cfg(feature = "net")]
         Syscall::SocketBind => sys_socket_bind(arg1, arg2, arg3),
+        #[cfg(feature = "net")]
         Syscall::SocketListen => sys_socket_listen(arg1, arg2),
+        #[cfg(feature = "net")]
         Syscall::SocketConnect => sys_socket_connect(arg1, arg2, arg3),
         // Linux ABI: accept4(fd, addr, addrlen_ptr, flags)
         // accept (Linux 43) also maps here via remap patch.
-        Syscall::SocketAccept => sys_socket_accept(arg1, arg2, arg3),
-        Syscall::SocketSend => sys_socket_send(arg1, arg2, arg3),
-        Syscall::SocketRecv => sys_socket_recv(arg1, arg2, arg3),
+        #[cfg(feature = "net")]
+        Syscall::SocketAccept => sys_socket_accept(arg1, arg2, arg3, arg4),
+        // send/recv(fd, buf, len, flags)
+        #[cfg(feature = "net")]
+        Syscall::SocketSend => sys_socket_send(arg1, arg2, arg3, arg4),
+        #[cfg(feature = "net")]
+        Syscall::SocketRecv => sys_socket_recv(arg1, arg2, arg3, arg4),
+        #[cfg(feature = "net")]
         Syscall::SocketClose => sys_socket_close(arg1),
         // Linux ABI: socketpair(domain, type, protocol, sv[2])
         // arg1=domain, arg2=type, arg3=protocol, arg4=sv pointer
+        #[cfg(feature = "net")]
         Syscall::SocketPair => sys_socket_pair(arg1, arg2, arg4),
 
         // Graphics / framebuffer (Phase 6)
@@ -898,30 +736,57 @@ fn handle_syscall(
         Syscall::InputPoll => sys_input_poll(arg1),
         Syscall::InputRead => sys_input_read(arg1, arg2),
         Syscall::FbSwap => sys_fb_swap(),
+        #[cfg(feature = "desktop")]
+        Syscall::Screenshot => sys_screenshot(arg1, arg2, arg3, arg4, arg5),
+        #[cfg(feature = "desktop")]
+        Syscall::Notify => sys_notify(arg1, arg2),
+        #[cfg(feature = "desktop")]
+        Syscall::Window => sys_window(arg1, arg2, arg3, arg4, arg5),
+        Syscall::InputInject => sys_input_inject(arg1, arg2, arg3),
+        Syscall::FbFlip => sys_fb_flip(arg1, arg2),
 
         // Wayland compositor (Phase 6)
+        #[cfg(feature = "desktop")]
         Syscall::WlConnect => sys_wl_connect(),
+        #[cfg(feature = "desktop")]
         Syscall::WlDisconnect => sys_wl_disconnect(arg1),
+        #[cfg(feature = "desktop")]
         Syscall::WlSendMessage => sys_wl_send_message(arg1, arg2, arg3),
+        #[cfg(feature = "desktop")]
         Syscall::WlRecvMessage => sys_wl_recv_message(arg1, arg2, arg3),
+        #[cfg(feature = "desktop")]
         Syscall::WlCreateShmPool => sys_wl_create_shm_pool(arg1, arg2),
+        #[cfg(feature = "desktop")]
         Syscall::WlCreateSurface => sys_wl_create_surface(arg1, arg2, arg3, arg4),
+        #[cfg(feature = "desktop")]
         Syscall::WlCommitSurface => sys_wl_commit_surface(arg1, arg2),
+        #[cfg(feature = "desktop")]
         Syscall::WlGetEvents => sys_wl_get_events(arg1, arg2, arg3),
 
         // Network extensions (Phase 6)
+        #[cfg(feature = "net")]
         Syscall::NetSendTo => sys_net_sendto(arg1, arg2, arg3, arg4, arg5),
+        #[cfg(feature = "net")]
         Syscall::NetRecvFrom => sys_net_recvfrom(arg1, arg2, arg3, arg4, arg5),
+        #[cfg(feature = "net")]
         Syscall::NetGetSockName => sys_net_getsockname(arg1, arg2, arg3),
+        #[cfg(feature = "net")]
         Syscall::NetGetPeerName => sys_net_getpeername(arg1, arg2, arg3),
+        #[cfg(feature = "net")]
         Syscall::NetSetSockOpt => sys_net_setsockopt(arg1, arg2, arg3, arg4, arg5),
-        Syscall::NetGetSockOpt => sys_net_getsockopt(arg1, arg2, arg3, arg4),
+        #[cfg(feature = "net")]
+        Syscall::NetGetSockOpt => sys_net_getsockopt(arg1, arg2, arg3, arg4, arg5),
+        #[cfg(feature = "net")]
+        Syscall::NetFirewall => sys_net_firewall(arg1, arg2, arg3),
+        #[cfg(feature = "net")]
+        Syscall::NetPacketRing => sys_net_packet_ring(arg1, arg2, arg3),
 
         // Resource limits (Phase 6.5)
         Syscall::GetRlimit => memory::sys_getrlimit(arg1, arg2),
         Syscall::SetRlimit => memory::sys_setrlimit(arg1, arg2),
 
         // epoll I/O multiplexing (Phase 6.5)
+        #[cfg(feature = "net")]
         Syscall::EpollCreate => {
             let _flags = arg1; // epoll_create1 flags (EPOLL_CLOEXEC)
             let cloexec = (arg1 & 0x80000) != 0; // EPOLL_CLOEXEC = O_CLOEXEC
@@ -941,6 +806,7 @@ fn handle_syscall(
                 .map_err(|_| SyscallError::OutOfMemory)?;
             Ok(fd)
         }
+        #[cfg(feature = "net")]
         Syscall::EpollCtl => {
             let epoll_fd = arg1;
             let op = arg2 as u32;
@@ -948,16 +814,15 @@ fn handle_syscall(
             let event_ptr = arg4;
             let epoll_id = resolve_epoll_id(epoll_fd)?;
             let event = if event_ptr != 0 {
-                validate_user_ptr_typed::<crate::net::epoll::EpollEvent>(event_ptr)?;
-                // SAFETY: event_ptr validated as aligned, non-null, and in user-space above.
-                Some(unsafe { &*(event_ptr as *const crate::net::epoll::EpollEvent) })
+                Some(copy_from_user::<crate::net::epoll::EpollEvent>(event_ptr)?)
             } else {
                 None
             };
-            crate::net::epoll::epoll_ctl(epoll_id, op, fd, event)
+            crate::net::epoll::epoll_ctl(epoll_id, op, fd, event.as_ref())
                 .map(|_| 0)
                 .map_err(|_| SyscallError::InvalidArgument)
         }
+        #[cfg(feature = "net")]
         Syscall::EpollWait => {
             let epoll_fd = arg1;
             let events_ptr = arg2;
@@ -967,20 +832,19 @@ fn handle_syscall(
             if max_events == 0 {
                 return Err(SyscallError::InvalidArgument);
             }
-            validate_user_buffer(
-                events_ptr,
-                max_events * core::mem::size_of::<crate::net::epoll::EpollEvent>(),
-            )?;
-            // SAFETY: events_ptr validated by validate_user_buffer above as non-null and in
-            // user-space.
-            let events = unsafe {
-                core::slice::from_raw_parts_mut(
-                    events_ptr as *mut crate::net::epoll::EpollEvent,
-                    max_events,
-                )
-            };
-            crate::net::epoll::epoll_wait(epoll_id, events, timeout_ms)
-                .map_err(|_| SyscallError::InvalidArgument)
+            // An instance never watches more than 1024 fds, so a larger
+            // array cannot fill up anyway.
+            let max_events = max_events.min(1024);
+            let event_size = core::mem::size_of::<crate::net::epoll::EpollEvent>();
+            access_ok(events_ptr, max_events * event_size, Access::Write)?;
+            let mut events = alloc::vec![
+                crate::net::epoll::EpollEvent { events: 0, data: 0 };
+                max_events
+            ];
+            let n = crate::net::epoll::epoll_wait(epoll_id, &mut events, timeout_ms)
+                .map_err(|_| SyscallError::InvalidArgument)?;
+            copy_slice_to_user(events_ptr, &events[..n])?;
+            Ok(n)
         }
         // Process groups / sessions (Phase 6.5) -- delegate to existing
         // implementations which also back the older syscall numbers 176-180.
@@ -1036,13 +900,19 @@ fn handle_syscall(
         // Audio syscalls (Phase 7) -- wired to audio subsystem
         Syscall::AudioOpen => {
             // arg1=sample_rate, arg2=channels -> returns stream_id
+            // (0 picks the 48000 Hz stereo default; other rates are rejected)
             let sample_rate = arg1 as u32;
-            let channels = if arg2 == 0 { 2u8 } else { arg2 as u8 };
+            let channels = if arg2 == 0 {
+                2u8
+            } else {
+                arg2.min(u8::MAX as usize) as u8
+            };
             let config = crate::audio::AudioConfig {
                 sample_rate: if sample_rate == 0 { 48000 } else { sample_rate },
                 channels,
                 format: crate::audio::SampleFormat::S16Le,
-                buffer_frames: 1024,
+                // 0: the client's default stream buffer
+                buffer_frames: 0,
             };
             crate::audio::client::with_client(|client| {
                 client
@@ -1050,7 +920,10 @@ fn handle_syscall(
                     .map(|id| id.as_u32() as usize)
             })
             .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|_| SyscallError::OutOfMemory)
+            .map_err(|e| match e {
+                crate::error::KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+                _ => SyscallError::OutOfMemory,
+            })
         }
         Syscall::AudioClose => {
             // arg1=stream_id
@@ -1065,20 +938,23 @@ fn handle_syscall(
             let stream_id = crate::audio::client::AudioStreamId(arg1 as u32);
             let buf_ptr = arg2;
             let sample_count = arg3;
-            let byte_len = sample_count * 2; // i16 = 2 bytes
-            validate_user_buffer(buf_ptr, byte_len)?;
-            // SAFETY: buf_ptr validated by validate_user_buffer above as non-null and in
-            // user-space.
-            let samples =
-                unsafe { core::slice::from_raw_parts(buf_ptr as *const i16, sample_count) };
-            crate::audio::client::with_client(|client| client.write_samples(stream_id, samples))
-                .map_err(|_| SyscallError::InvalidState)?
-                .map_err(|_| SyscallError::InvalidArgument)
+            let samples = copy_slice_from_user::<i16>(buf_ptr, sample_count)?;
+            let written = crate::audio::client::with_client(|client| {
+                client.write_samples(stream_id, &samples)
+            })
+            .map_err(|_| SyscallError::InvalidState)?
+            .map_err(|_| SyscallError::InvalidArgument)?;
+            // Start playing right away instead of on the next timer tick
+            crate::audio::pipeline::pump();
+            Ok(written)
         }
         Syscall::AudioSetVolume => {
             // arg1=stream_id, arg2=volume (0-100)
             let stream_id = crate::audio::client::AudioStreamId(arg1 as u32);
-            let volume = arg2 as u16;
+            if arg2 > 100 {
+                return Err(SyscallError::InvalidArgument);
+            }
+            let volume = (arg2 * 65535 / 100) as u16;
             crate::audio::client::with_client(|client| client.set_volume(stream_id, volume))
                 .map_err(|_| SyscallError::InvalidState)?
                 .map_err(|_| SyscallError::InvalidArgument)?;
@@ -1087,7 +963,6 @@ fn handle_syscall(
         Syscall::AudioGetInfo => {
             // arg1=info_ptr -> writes (sample_rate: u32, channels: u32, streams: u32)
             let info_ptr = arg1;
-            validate_user_buffer(info_ptr, 12)?; // 3 x u32
             let info = crate::audio::client::with_client(|client| {
                 (
                     client.default_sample_rate(),
@@ -1096,13 +971,7 @@ fn handle_syscall(
                 )
             })
             .map_err(|_| SyscallError::InvalidState)?;
-            // SAFETY: info_ptr validated by validate_user_buffer(info_ptr, 12) above.
-            unsafe {
-                let ptr = info_ptr as *mut u32;
-                ptr.write(info.0);
-                ptr.add(1).write(info.1);
-                ptr.add(2).write(info.2);
-            }
+            copy_to_user(info_ptr, &[info.0, info.1, info.2])?;
             Ok(0)
         }
         Syscall::AudioStart => {
@@ -1156,20 +1025,15 @@ fn handle_syscall(
         Syscall::EventfdRead => {
             let efd_id = arg1 as u32;
             let buf_ptr = arg2;
-            validate_user_buffer(buf_ptr, 8)?;
+            access_ok(buf_ptr, 8, Access::Write)?;
             let val = crate::fs::eventfd::eventfd_read(efd_id)?;
-            // SAFETY: buf_ptr validated by validate_user_buffer above.
-            unsafe {
-                *(buf_ptr as *mut u64) = val;
-            }
+            copy_to_user(buf_ptr, &val)?;
             Ok(8)
         }
         Syscall::EventfdWrite => {
             let efd_id = arg1 as u32;
             let buf_ptr = arg2;
-            validate_user_buffer(buf_ptr, 8)?;
-            // SAFETY: buf_ptr validated by validate_user_buffer above.
-            let val = unsafe { *(buf_ptr as *const u64) };
+            let val: u64 = copy_from_user(buf_ptr)?;
             crate::fs::eventfd::eventfd_write(efd_id, val)
         }
 
@@ -1197,28 +1061,27 @@ fn handle_syscall(
             let new_ptr = arg3;
             let old_ptr = arg4;
             let tfd_id = resolve_timerfd_id(fd)?;
-            validate_user_ptr_typed::<crate::fs::timerfd::Itimerspec>(new_ptr)?;
-            // SAFETY: new_ptr validated above.
-            let new_spec = unsafe { &*(new_ptr as *const crate::fs::timerfd::Itimerspec) };
-            let old_spec = if old_ptr != 0 {
-                validate_user_ptr_typed::<crate::fs::timerfd::Itimerspec>(old_ptr)?;
-                // SAFETY: old_ptr validated above.
-                Some(unsafe { &mut *(old_ptr as *mut crate::fs::timerfd::Itimerspec) })
-            } else {
-                None
-            };
-            crate::fs::timerfd::timerfd_settime(tfd_id, flags, new_spec, old_spec)
+            let new_spec: crate::fs::timerfd::Itimerspec = copy_from_user(new_ptr)?;
+            if old_ptr == 0 {
+                return crate::fs::timerfd::timerfd_settime(tfd_id, flags, &new_spec, None);
+            }
+            access_ok(
+                old_ptr,
+                core::mem::size_of::<crate::fs::timerfd::Itimerspec>(),
+                Access::Write,
+            )?;
+            let mut old_spec = crate::fs::timerfd::Itimerspec::default();
+            let ret =
+                crate::fs::timerfd::timerfd_settime(tfd_id, flags, &new_spec, Some(&mut old_spec))?;
+            copy_to_user(old_ptr, &old_spec)?;
+            Ok(ret)
         }
         Syscall::TimerfdGettime => {
             let fd = arg1;
             let curr_ptr = arg2;
             let tfd_id = resolve_timerfd_id(fd)?;
-            validate_user_ptr_typed::<crate::fs::timerfd::Itimerspec>(curr_ptr)?;
             let spec = crate::fs::timerfd::timerfd_gettime(tfd_id)?;
-            // SAFETY: curr_ptr validated above.
-            unsafe {
-                *(curr_ptr as *mut crate::fs::timerfd::Itimerspec) = spec;
-            }
+            copy_to_user(curr_ptr, &spec)?;
             Ok(0)
         }
 
@@ -1245,7 +1108,9 @@ fn handle_syscall(
         }
 
         // sendmsg/recvmsg -- delegate to unix socket module for SCM_RIGHTS
+        #[cfg(feature = "net")]
         Syscall::SendMsg => sys_sendmsg(arg1, arg2, arg3),
+        #[cfg(feature = "net")]
         Syscall::RecvMsg => sys_recvmsg(arg1, arg2, arg3),
 
         // musl libc compatibility syscalls
@@ -1270,7 +1135,53 @@ fn handle_syscall(
         Syscall::SetRobustList => sys_set_robust_list(arg1, arg2),
         Syscall::ClockNanosleep => sys_clock_nanosleep(arg1, arg2, arg3, arg4),
 
-        _ => Err(SyscallError::InvalidSyscall),
+        // Credential syscalls
+        Syscall::Setreuid => sys_setreuid(arg1, arg2),
+        Syscall::Setregid => sys_setregid(arg1, arg2),
+        Syscall::Getresuid => sys_getresuid(arg1, arg2, arg3),
+        Syscall::Getresgid => sys_getresgid(arg1, arg2, arg3),
+
+        Syscall::Crypt => sys_crypt(arg1, arg2, arg3, arg4),
+
+        Syscall::Setitimer => sys_setitimer(arg1, arg2, arg3),
+        Syscall::Getitimer => sys_getitimer(arg1, arg2),
+        Syscall::Getrusage => sys_getrusage(arg1, arg2),
+
+        Syscall::CryptoHash => sys_crypto_hash(arg1, arg2, arg3, arg4, arg5),
+        Syscall::CryptoHmac => sys_crypto_hmac(arg1, arg2),
+        #[cfg(feature = "net")]
+        Syscall::CryptoAeadSeal => sys_crypto_aead_seal(arg1, arg2),
+        #[cfg(feature = "net")]
+        Syscall::CryptoAeadOpen => sys_crypto_aead_open(arg1, arg2),
+        Syscall::CryptoX25519 => sys_crypto_x25519(arg1, arg2, arg3),
+        Syscall::CryptoVerify => sys_crypto_verify(arg1, arg2),
+        Syscall::CryptoSign => sys_crypto_sign(arg1, arg2),
+
+        #[cfg(feature = "net")]
+        Syscall::Sendfile => sys_sendfile(arg1, arg2, arg3, arg4),
+        #[cfg(feature = "net")]
+        Syscall::Splice => sys_splice(arg1, arg2, arg3, arg4, arg5),
+
+        Syscall::Getxattr => sys_getxattr(arg1, arg2, arg3, arg4),
+        Syscall::Setxattr => sys_setxattr(arg1, arg2, arg3, arg4, arg5),
+        Syscall::Listxattr => sys_listxattr(arg1, arg2, arg3),
+        Syscall::Removexattr => sys_removexattr(arg1, arg2),
+        Syscall::Fgetxattr => sys_fgetxattr(arg1, arg2, arg3, arg4),
+        Syscall::Fsetxattr => sys_fsetxattr(arg1, arg2, arg3, arg4, arg5),
+        Syscall::Flistxattr => sys_flistxattr(arg1, arg2, arg3),
+        Syscall::Fremovexattr => sys_fremovexattr(arg1, arg2),
+
+        Syscall::Fallocate => sys_fallocate(arg1, arg2, arg3, arg4),
+
+        Syscall::Times => sys_times(arg1),
+
+        Syscall::HeapProfile => sys_heap_profile(arg1, arg2),
+
+        Syscall::SyscallFilter => sys_syscall_filter(arg1, arg2, arg3, arg4, arg5),
+
+        // Syscalls of subsystems left out of this build
+        #[cfg(not(all(feature = "net", feature = "pkg", feature = "desktop")))]
+        _ => Err(SyscallError::NotImplemented),
     }
 }
 
@@ -1290,6 +1201,7 @@ fn resolve_timerfd_id(fd: usize) -> Result<u32, SyscallError> {
 }
 
 /// Resolve a file descriptor to an internal epoll ID.
+#[cfg(feature = "net")]
 fn resolve_epoll_id(fd: usize) -> Result<u32, SyscallError> {
     let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
@@ -1307,20 +1219,26 @@ fn resolve_epoll_id(fd: usize) -> Result<u32, SyscallError> {
 /// # Arguments
 /// - `buf_ptr`: User-space buffer to fill.
 /// - `buflen`: Number of bytes to generate.
-/// - `flags`: 0 for blocking (always succeeds), GRND_NONBLOCK (1) for
-///   non-blocking.
-fn sys_getrandom(buf_ptr: usize, buflen: usize, _flags: usize) -> SyscallResult {
+/// - `flags`: any combination of GRND_NONBLOCK (1), GRND_RANDOM (2) and
+///   GRND_INSECURE (4). The CSPRNG is seeded before init runs, so none of them
+///   change behavior; unknown bits are rejected.
+fn sys_getrandom(buf_ptr: usize, buflen: usize, flags: usize) -> SyscallResult {
+    const GRND_VALID: usize = 0x1 | 0x2 | 0x4;
+    if flags & !GRND_VALID != 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
     if buflen == 0 {
         return Ok(0);
     }
     // Cap at 256 bytes per call to avoid holding the RNG lock too long
     let len = buflen.min(256);
-    validate_user_buffer(buf_ptr, len)?;
+    access_ok(buf_ptr, len, Access::Write)?;
 
     let rng = crate::crypto::random::get_random();
-    // SAFETY: buf_ptr validated by validate_user_buffer above.
-    let buf = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, len) };
-    rng.fill_bytes(buf).map_err(|_| SyscallError::IoError)?;
+    let mut buf = [0u8; 256];
+    rng.fill_bytes(&mut buf[..len])
+        .map_err(|_| SyscallError::IoError)?;
+    copy_bytes_to_user(buf_ptr, &buf[..len])?;
     Ok(len)
 }
 
@@ -1341,7 +1259,7 @@ fn sys_getdents64(fd: usize, buf_ptr: usize, buf_size: usize) -> SyscallResult {
     if buf_size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(buf_ptr, buf_size)?;
+    access_ok(buf_ptr, buf_size, Access::Write)?;
 
     let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
     let file_table = proc.file_table.lock();
@@ -1357,6 +1275,7 @@ fn sys_getdents64(fd: usize, buf_ptr: usize, buf_size: usize) -> SyscallResult {
         return Ok(0);
     }
 
+    let mut out = alloc::vec::Vec::new();
     let mut offset = 0usize;
     let mut idx = pos;
 
@@ -1382,36 +1301,29 @@ fn sys_getdents64(fd: usize, buf_ptr: usize, buf_size: usize) -> SyscallResult {
             crate::fs::NodeType::Socket => 12,     // DT_SOCK
         };
 
-        // SAFETY: buf_ptr + offset is within the validated user buffer.
-        unsafe {
-            let base = (buf_ptr + offset) as *mut u8;
-            // d_ino (use inode from entry, default 1)
-            let ino = if entry.inode == 0 {
-                (idx + 1) as u64
-            } else {
-                entry.inode
-            };
-            *(base as *mut u64) = ino;
-            // d_off (offset to next entry)
-            *((base.add(8)) as *mut u64) = (offset + reclen) as u64;
-            // d_reclen
-            *((base.add(16)) as *mut u16) = reclen as u16;
-            // d_type
-            *base.add(18) = d_type;
-            // d_name (NUL-terminated)
-            core::ptr::copy_nonoverlapping(name_bytes.as_ptr(), base.add(19), name_bytes.len());
-            *base.add(19 + name_bytes.len()) = 0;
-            // Zero-fill padding
-            let written = 19 + name_bytes.len() + 1;
-            for i in written..reclen {
-                *base.add(i) = 0;
-            }
-        }
+        // d_ino (use inode from entry, default 1)
+        let ino = if entry.inode == 0 {
+            (idx + 1) as u64
+        } else {
+            entry.inode
+        };
+        out.extend_from_slice(&ino.to_ne_bytes());
+        // d_off (offset to next entry)
+        out.extend_from_slice(&((offset + reclen) as u64).to_ne_bytes());
+        // d_reclen
+        out.extend_from_slice(&(reclen as u16).to_ne_bytes());
+        // d_type
+        out.push(d_type);
+        // d_name, then the NUL terminator and zero padding
+        out.extend_from_slice(name_bytes);
+        out.resize(offset + reclen, 0);
 
         offset += reclen;
         idx += 1;
     }
 
+    copy_bytes_to_user(buf_ptr, &out)?;
+
     // Advance file position
     if idx > pos {
         let _ = file_desc.seek(crate::fs::SeekFrom::Start(idx));
@@ -1423,26 +1335,40 @@ fn sys_getdents64(fd: usize, buf_ptr: usize, buf_size: usize) -> SyscallResult {
 /// prlimit64 syscall -- get/set resource limits for a process.
 ///
 /// Combines getrlimit and setrlimit in one call. musl uses this for both.
+/// Another process's limits may be read or changed by root or by a caller
+/// with the same real user ID.
 ///
 /// # Arguments
-/// - `pid`: Process ID (0 = current process).
+/// - `pid`: Target process (0 = current).
 /// - `resource`: RLIMIT_* constant.
 /// - `new_rlim_ptr`: Pointer to new Rlimit (0 = don't set).
 /// - `old_rlim_ptr`: Pointer to receive old Rlimit (0 = don't get).
 fn sys_prlimit64(
-    _pid: usize,
+    pid: usize,
     resource: usize,
     new_rlim_ptr: usize,
     old_rlim_ptr: usize,
 ) -> SyscallResult {
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let target = if pid == 0 || pid as u64 == caller.pid.0 {
+        caller
+    } else {
+        let target = crate::process::get_process(crate::process::ProcessId(pid as u64))
+            .ok_or(SyscallError::ProcessNotFound)?;
+        if caller.euid() != 0 && caller.uid() != target.uid() {
+            return Err(SyscallError::PermissionDenied);
+        }
+        target
+    };
+
     // Get old limits if requested
     if old_rlim_ptr != 0 {
-        memory::sys_getrlimit(resource, old_rlim_ptr)?;
+        memory::get_rlimit(target, resource, old_rlim_ptr)?;
     }
 
     // Set new limits if requested
     if new_rlim_ptr != 0 {
-        memory::sys_setrlimit(resource, new_rlim_ptr)?;
+        memory::set_rlimit(target, resource, new_rlim_ptr)?;
     }
 
     Ok(0)
@@ -1457,12 +1383,14 @@ fn sys_prlimit64(
 fn sys_fchmodat(dirfd: usize, path_ptr: usize, mode: usize) -> SyscallResult {
     let rel_path = filesystem::read_user_path(path_ptr)?;
     let abs_path = filesystem::resolve_at_path(dirfd, &rel_path)?;
+    filesystem::require_sandbox(dirfd, &abs_path, crate::security::AccessType::Write, true)?;
 
     let vfs_lock = filesystem::vfs()?;
     let vfs_guard = vfs_lock.read();
     let node = vfs_guard
         .resolve_path(&abs_path)
         .map_err(filesystem::map_resolve_err)?;
+    filesystem::require_owner(&*node, &filesystem::current_credentials())?;
     let perms = crate::fs::Permissions::from_mode(mode as u32);
     node.chmod(perms)
         .map_err(|_| SyscallError::InvalidArgument)?;
@@ -1480,14 +1408,20 @@ fn sys_fchmodat(dirfd: usize, path_ptr: usize, mode: usize) -> SyscallResult {
 fn sys_fchownat(
     dirfd: usize,
     path_ptr: usize,
-    _uid: usize,
-    _gid: usize,
+    uid: usize,
+    gid: usize,
     _flags: usize,
 ) -> SyscallResult {
     let rel_path = filesystem::read_user_path(path_ptr)?;
-    let _abs_path = filesystem::resolve_at_path(dirfd, &rel_path)?;
-    // No-op: accept but don't enforce ownership changes (same as sys_chown)
-    Ok(0)
+    let abs_path = filesystem::resolve_at_path(dirfd, &rel_path)?;
+    filesystem::require_sandbox(dirfd, &abs_path, crate::security::AccessType::Write, true)?;
+
+    let vfs_lock = filesystem::vfs()?;
+    let vfs_guard = vfs_lock.read();
+    let node = vfs_guard
+        .resolve_path(&abs_path)
+        .map_err(filesystem::map_resolve_err)?;
+    filesystem::chown_node(&*node, uid as u32, gid as u32)
 }
 
 /// linkat syscall -- create hard link relative to directory fds.
@@ -1509,24 +1443,7 @@ fn sys_linkat(
     let new_rel = filesystem::read_user_path(newpath_ptr)?;
     let old_abs = filesystem::resolve_at_path(olddirfd, &old_rel)?;
     let new_abs = filesystem::resolve_at_path(newdirfd, &new_rel)?;
-
-    let vfs_lock = filesystem::vfs()?;
-    let vfs_guard = vfs_lock.read();
-
-    // Resolve old path to get target node
-    let target = vfs_guard
-        .resolve_path(&old_abs)
-        .map_err(filesystem::map_resolve_err)?;
-
-    // Split new path into parent + name, create link in parent
-    let (parent_path, link_name) = filesystem::split_path(&new_abs)?;
-    let parent = vfs_guard
-        .resolve_path(&parent_path)
-        .map_err(filesystem::map_resolve_err)?;
-    parent
-        .link(&link_name, target)
-        .map_err(|_| SyscallError::InvalidArgument)?;
-    Ok(0)
+    filesystem::link_paths(&old_abs, &new_abs)
 }
 
 /// symlinkat syscall -- create symlink relative to a directory fd.
@@ -1539,6 +1456,12 @@ fn sys_symlinkat(target_ptr: usize, newdirfd: usize, linkpath_ptr: usize) -> Sys
     let target = filesystem::read_user_path(target_ptr)?;
     let link_rel = filesystem::read_user_path(linkpath_ptr)?;
     let link_abs = filesystem::resolve_at_path(newdirfd, &link_rel)?;
+    filesystem::require_sandbox(
+        newdirfd,
+        &link_abs,
+        crate::security::AccessType::Write,
+        false,
+    )?;
 
     let vfs_lock = filesystem::vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1567,7 +1490,8 @@ fn sys_readlinkat(dirfd: usize, path_ptr: usize, buf_ptr: usize, buf_size: usize
     }
     let rel_path = filesystem::read_user_path(path_ptr)?;
     let abs_path = filesystem::resolve_at_path(dirfd, &rel_path)?;
-    validate_user_buffer(buf_ptr, buf_size)?;
+    filesystem::require_sandbox(dirfd, &abs_path, crate::security::AccessType::Read, false)?;
+    access_ok(buf_ptr, buf_size, Access::Write)?;
 
     let vfs_lock = filesystem::vfs()?;
     let vfs_guard = vfs_lock.read();
@@ -1580,10 +1504,7 @@ fn sys_readlinkat(dirfd: usize, path_ptr: usize, buf_ptr: usize, buf_size: usize
 
     let bytes = target.as_bytes();
     let copy_len = bytes.len().min(buf_size);
-    // SAFETY: buf_ptr validated by validate_user_buffer above.
-    unsafe {
-        core::ptr::copy_nonoverlapping(bytes.as_ptr(), buf_ptr as *mut u8, copy_len);
-    }
+    copy_bytes_to_user(buf_ptr, &bytes[..copy_len])?;
     Ok(copy_len)
 }
 
@@ -1622,7 +1543,7 @@ fn sys_set_tid_address(tidptr: usize) -> SyscallResult {
     let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
 
     if tidptr != 0 {
-        validate_user_pointer(tidptr, 4)?;
+        user_range(tidptr, 4)?;
     }
 
     // Store on both the process PCB (for process-level tracking) and the
@@ -1662,7 +1583,7 @@ fn sys_set_robust_list(head_ptr: usize, len: usize) -> SyscallResult {
         return Err(SyscallError::InvalidArgument);
     }
     if head_ptr != 0 {
-        validate_user_pointer(head_ptr, len)?;
+        user_range(head_ptr, len)?;
     }
 
     let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -1689,252 +1610,275 @@ fn sys_clock_nanosleep(
     time::sys_nanosleep(req_ptr, rem_ptr)
 }
 
+/// `struct msghdr` (64-bit Linux layout)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct MsgHdr {
+    name: usize,
+    namelen: u32,
+    _pad0: u32,
+    iov: usize,
+    iovlen: usize,
+    control: usize,
+    controllen: usize,
+    flags: i32,
+    _pad1: u32,
+}
+
+/// Ancillary data constants (`<sys/socket.h>`)
+const SOL_SOCKET: i32 = 1;
+const SCM_RIGHTS: i32 = 1;
+/// `struct cmsghdr`: size_t cmsg_len, int cmsg_level, int cmsg_type
+const CMSGHDR_SIZE: usize = 16;
+/// recvmsg `msg_flags`: ancillary data was truncated
+const MSG_CTRUNC: i32 = 0x08;
+/// Maximum number of iovec entries (IOV_MAX)
+const IOV_MAX: usize = 1024;
+
+/// The (base, len) pairs of a user iovec array.
+fn user_iovecs(
+    iov_ptr: usize,
+    iov_len: usize,
+) -> Result<alloc::vec::Vec<(usize, usize)>, SyscallError> {
+    if iov_len == 0 || iov_ptr == 0 {
+        return Ok(alloc::vec::Vec::new());
+    }
+    if iov_len > IOV_MAX {
+        return Err(SyscallError::InvalidArgument);
+    }
+    Ok(copy_slice_from_user::<[usize; 2]>(iov_ptr, iov_len)?
+        .into_iter()
+        .map(|[base, len]| (base, len))
+        .collect())
+}
+
 /// sendmsg syscall -- sends data with optional ancillary data (SCM_RIGHTS).
 ///
 /// arg1=socket_fd, arg2=msghdr_ptr, arg3=flags.
 ///
-/// The msghdr struct layout (matching C struct msghdr):
-///   - msg_name (ptr), msg_namelen (u32)
-///   - msg_iov (ptr to iovec array), msg_iovlen (i32)
-///   - msg_control (ptr to cmsghdr), msg_controllen (u32)
-///   - msg_flags (i32)
-///
-/// For SCM_RIGHTS: msg_control points to a cmsghdr with cmsg_level=SOL_SOCKET,
-/// cmsg_type=SCM_RIGHTS, followed by an array of i32 file descriptors.
-fn sys_sendmsg(socket_fd: usize, msghdr_ptr: usize, _flags: usize) -> SyscallResult {
-    // Validate msghdr pointer (7 fields, ~56 bytes on 64-bit)
-    validate_user_buffer(msghdr_ptr, 56)?;
-
-    // SAFETY: msghdr_ptr validated above.
-    let (iov_ptr, iov_len, control_ptr, control_len) = unsafe {
-        let base = msghdr_ptr as *const usize;
-        // msg_name = offset 0, msg_namelen = offset 1
-        // msg_iov = offset 2, msg_iovlen = offset 3
-        let iov_ptr = *base.add(2);
-        let iov_len = *base.add(3);
-        // msg_control = offset 4, msg_controllen = offset 5
-        let control_ptr = *base.add(4);
-        let control_len = *base.add(5);
-        (iov_ptr, iov_len, control_ptr, control_len)
-    };
+/// For SCM_RIGHTS: msg_control holds cmsghdrs with cmsg_level=SOL_SOCKET,
+/// cmsg_type=SCM_RIGHTS, each followed by an array of i32 file
+/// descriptors. They are resolved in the sender's file table here, so the
+/// open files stay alive while the message is queued. On a Unix datagram
+/// socket `msg_name` selects the destination. `flags` takes MSG_DONTWAIT.
+#[cfg(feature = "net")]
+fn sys_sendmsg(socket_fd: usize, msghdr_ptr: usize, flags: usize) -> SyscallResult {
+    let hdr: MsgHdr = copy_from_user(msghdr_ptr)?;
 
     // Gather data from iovec array
     let mut data = alloc::vec::Vec::new();
-    if iov_len > 0 && iov_ptr != 0 {
-        let iov_byte_len = iov_len * core::mem::size_of::<[usize; 2]>();
-        validate_user_buffer(iov_ptr, iov_byte_len)?;
-        for i in 0..iov_len {
-            // SAFETY: iov_ptr validated above, each iovec is (base, len).
-            let (base, len) = unsafe {
-                let entry = (iov_ptr as *const usize).add(i * 2);
-                (*entry, *entry.add(1))
-            };
-            if len > 0 && base != 0 {
-                validate_user_buffer(base, len)?;
-                // SAFETY: base validated above.
-                let slice = unsafe { core::slice::from_raw_parts(base as *const u8, len) };
-                data.extend_from_slice(slice);
-            }
+    for (base, len) in user_iovecs(hdr.iov, hdr.iovlen)? {
+        if len > 0 && base != 0 {
+            data.extend_from_slice(&copy_slice_from_user::<u8>(base, len)?);
         }
     }
 
-    // Parse ancillary data for SCM_RIGHTS
-    let rights = if control_len >= 16 && control_ptr != 0 {
-        validate_user_buffer(control_ptr, control_len)?;
-        parse_scm_rights(control_ptr, control_len)
+    if let SocketHandle::Inet(_) = socket_handle(socket_fd)? {
+        // INET sockets don't support SCM_RIGHTS -- just send data
+        return socket_io(socket_fd, flags, SocketWait::Send, |handle| match handle {
+            SocketHandle::Inet(id) => crate::net::socket::with_socket_mut(id, |s| s.send(&data, 0))
+                .map_err(map_kernel_error)?
+                .map_err(map_kernel_error),
+            SocketHandle::Unix(_) => Err(SyscallError::InvalidState),
+        });
+    }
+
+    let rights = if hdr.control != 0 && hdr.controllen >= CMSGHDR_SIZE {
+        collect_scm_rights(hdr.control, hdr.controllen)?
     } else {
         None
     };
-
-    // Send via Unix socket with optional SCM_RIGHTS
-    if is_inet_socket(socket_fd) {
-        // INET sockets don't support SCM_RIGHTS -- just send data
-        let id = inet_socket_id(socket_fd);
-        crate::net::socket::with_socket_mut(id, |s| s.send(&data, 0))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            })
+    let dest = if hdr.name != 0 && hdr.namelen > 0 {
+        Some(network_ext_syscalls::parse_sockaddr_un(
+            hdr.name,
+            hdr.namelen as usize,
+        )?)
     } else {
-        crate::net::unix_socket::socket_send(socket_fd as u64, &data, rights)
-            .map_err(|_| SyscallError::InvalidState)
-    }
+        None
+    };
+
+    socket_io(socket_fd, flags, SocketWait::Send, |handle| {
+        let SocketHandle::Unix(id) = handle else {
+            return Err(SyscallError::InvalidState);
+        };
+        match dest {
+            Some(ref path) => {
+                crate::net::unix_socket::socket_sendto(id, &data, path, rights.clone())
+            }
+            None => crate::net::unix_socket::socket_send(id, &data, rights.clone()),
+        }
+        .map_err(map_kernel_error)
+    })
 }
 
-/// Parse SCM_RIGHTS from a control message buffer.
-///
-/// Looks for a cmsghdr with cmsg_level=SOL_SOCKET(1), cmsg_type=SCM_RIGHTS(1),
-/// and extracts the file descriptor array.
-fn parse_scm_rights(
+/// Resolve the SCM_RIGHTS descriptors of a control message buffer to the
+/// caller's open files.
+#[cfg(feature = "net")]
+fn collect_scm_rights(
     control_ptr: usize,
     control_len: usize,
-) -> Option<crate::net::unix_socket::ScmRights> {
-    const SOL_SOCKET: i32 = 1;
-    const SCM_RIGHTS: i32 = 1;
-    const CMSGHDR_SIZE: usize = 16; // cmsg_len(4) + cmsg_level(4) + cmsg_type(4) + padding
-
-    if control_len < CMSGHDR_SIZE {
-        return None;
-    }
-
-    // SAFETY: control_ptr was validated by caller.
-    let (cmsg_len, cmsg_level, cmsg_type) = unsafe {
-        let base = control_ptr as *const u32;
-        (*base as usize, *base.add(1) as i32, *base.add(2) as i32)
+) -> Result<Option<crate::net::unix_socket::ScmRights>, SyscallError> {
+    let control = copy_slice_from_user::<u8>(control_ptr, control_len)?;
+    let word = |at: usize| -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&control[at..at + 8]);
+        bytes
+    };
+    let int = |at: usize| -> i32 {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&control[at..at + 4]);
+        i32::from_ne_bytes(bytes)
     };
 
-    if cmsg_level != SOL_SOCKET || cmsg_type != SCM_RIGHTS {
-        return None;
-    }
+    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = process.file_table.lock();
 
-    // FDs start after the cmsghdr (at CMSGHDR_SIZE offset)
-    let fd_bytes = cmsg_len.saturating_sub(CMSGHDR_SIZE);
-    let fd_count = fd_bytes / 4; // Each fd is an i32 (4 bytes)
-    if fd_count == 0 || fd_count > 16 {
-        return None;
-    }
+    let mut files = alloc::vec::Vec::new();
+    let mut offset = 0;
+    while offset + CMSGHDR_SIZE <= control_len {
+        let cmsg_len = usize::from_ne_bytes(word(offset));
+        let (cmsg_level, cmsg_type) = (int(offset + 8), int(offset + 12));
+        if cmsg_len < CMSGHDR_SIZE || cmsg_len > control_len - offset {
+            return Err(SyscallError::InvalidArgument);
+        }
 
-    let mut fds = alloc::vec::Vec::with_capacity(fd_count);
-    // SAFETY: control_ptr + CMSGHDR_SIZE is within the validated buffer.
-    for i in 0..fd_count {
-        let fd = unsafe { *((control_ptr + CMSGHDR_SIZE) as *const i32).add(i) };
-        if fd >= 0 {
-            fds.push(fd as u32);
+        if cmsg_level == SOL_SOCKET && cmsg_type == SCM_RIGHTS {
+            for i in 0..(cmsg_len - CMSGHDR_SIZE) / 4 {
+                let fd = int(offset + CMSGHDR_SIZE + i * 4);
+                let file = usize::try_from(fd)
+                    .ok()
+                    .and_then(|fd| file_table.get(fd))
+                    .ok_or(SyscallError::BadFileDescriptor)?;
+                files.push(file);
+            }
         }
+        // CMSG_ALIGN
+        offset += (cmsg_len + 7) & !7;
     }
 
-    if fds.is_empty() {
-        None
-    } else {
-        Some(crate::net::unix_socket::ScmRights { fds })
+    if files.len() > crate::net::unix_socket::SCM_RIGHTS_MAX {
+        return Err(SyscallError::InvalidArgument);
     }
+    Ok((!files.is_empty()).then_some(crate::net::unix_socket::ScmRights { files }))
 }
 
 /// recvmsg syscall -- receives data with optional ancillary data (SCM_RIGHTS).
 ///
 /// arg1=socket_fd, arg2=msghdr_ptr, arg3=flags.
 ///
-/// On return, if SCM_RIGHTS fds were received, they are written into the
-/// msg_control buffer as a cmsghdr.
-fn sys_recvmsg(socket_fd: usize, msghdr_ptr: usize, _flags: usize) -> SyscallResult {
-    validate_user_buffer(msghdr_ptr, 56)?;
-
-    // SAFETY: msghdr_ptr validated above.
-    let (iov_ptr, iov_len, control_ptr, control_len) = unsafe {
-        let base = msghdr_ptr as *const usize;
-        let iov_ptr = *base.add(2);
-        let iov_len = *base.add(3);
-        let control_ptr = *base.add(4);
-        let control_len = *base.add(5);
-        (iov_ptr, iov_len, control_ptr, control_len)
-    };
+/// Received descriptors are installed in the caller's file table and
+/// returned in msg_control as one SCM_RIGHTS cmsghdr; those that do not
+/// fit are closed and MSG_CTRUNC is set. For Unix sockets, msg_name gets
+/// the sender's address. `flags` takes MSG_DONTWAIT.
+#[cfg(feature = "net")]
+fn sys_recvmsg(socket_fd: usize, msghdr_ptr: usize, flags: usize) -> SyscallResult {
+    let mut hdr: MsgHdr = copy_from_user(msghdr_ptr)?;
+    let iovecs = user_iovecs(hdr.iov, hdr.iovlen)?;
 
     // Calculate total receive buffer size from iovec
-    let mut total_buf_len: usize = 0;
-    if iov_len > 0 && iov_ptr != 0 {
-        let iov_byte_len = iov_len * core::mem::size_of::<[usize; 2]>();
-        validate_user_buffer(iov_ptr, iov_byte_len)?;
-        for i in 0..iov_len {
-            // SAFETY: iov_ptr validated above.
-            let len = unsafe { *((iov_ptr as *const usize).add(i * 2 + 1)) };
-            total_buf_len = total_buf_len.saturating_add(len);
-        }
-    }
+    let total_buf_len = iovecs
+        .iter()
+        .fold(0usize, |total, &(_, len)| total.saturating_add(len));
 
     // Allocate a temporary kernel buffer to receive into
     let mut recv_buf = alloc::vec![0u8; total_buf_len.min(65536)];
 
     // Receive from socket
-    let (received, rights) = if is_inet_socket(socket_fd) {
-        let id = inet_socket_id(socket_fd);
-        let received = crate::net::socket::with_socket_mut(id, |s| s.recv(&mut recv_buf, 0))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            })?;
-        (received, None)
-    } else {
-        crate::net::unix_socket::socket_recv(socket_fd as u64, &mut recv_buf).map_err(
-            |e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            },
-        )?
-    };
+    let handle = socket_handle(socket_fd)?;
+    let (received, rights, from) =
+        socket_io(socket_fd, flags, SocketWait::Recv, |handle| match handle {
+            SocketHandle::Inet(id) => {
+                crate::net::socket::with_socket_mut(id, |s| s.recv(&mut recv_buf, 0))
+                    .map_err(map_kernel_error)?
+                    .map(|received| (received, None, None))
+                    .map_err(map_kernel_error)
+            }
+            SocketHandle::Unix(id) => crate::net::unix_socket::socket_recv_from(id, &mut recv_buf)
+                .map(|r| (r.len, r.rights, r.from))
+                .map_err(map_kernel_error),
+        })?;
 
     // Scatter received data into iovec buffers
     let mut offset = 0usize;
-    if iov_len > 0 && iov_ptr != 0 {
-        for i in 0..iov_len {
-            if offset >= received {
-                break;
-            }
-            // SAFETY: iov_ptr validated above.
-            let (base, len) = unsafe {
-                let entry = (iov_ptr as *const usize).add(i * 2);
-                (*entry, *entry.add(1))
-            };
-            if len > 0 && base != 0 {
-                let copy_len = (received - offset).min(len);
-                validate_user_buffer(base, copy_len)?;
-                // SAFETY: base validated above.
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        recv_buf[offset..].as_ptr(),
-                        base as *mut u8,
-                        copy_len,
-                    );
-                }
-                offset += copy_len;
-            }
+    for (base, len) in iovecs {
+        if offset >= received {
+            break;
+        }
+        if len > 0 && base != 0 {
+            let copy_len = (received - offset).min(len);
+            copy_bytes_to_user(base, &recv_buf[offset..offset + copy_len])?;
+            offset += copy_len;
         }
     }
 
-    // Write SCM_RIGHTS into msg_control if fds were received
+    hdr.flags = 0;
+    let mut control_used = 0;
     if let Some(scm) = rights {
-        if !scm.fds.is_empty() && control_ptr != 0 && control_len >= 16 {
-            write_scm_rights(control_ptr, control_len, &scm.fds, msghdr_ptr);
+        let (used, truncated) = install_scm_rights(hdr.control, hdr.controllen, scm)?;
+        control_used = used;
+        if truncated {
+            hdr.flags |= MSG_CTRUNC;
         }
     }
+    hdr.controllen = control_used;
 
+    if hdr.name != 0 && matches!(handle, SocketHandle::Unix(_)) {
+        hdr.namelen =
+            network_ext_syscalls::put_sockaddr_un(hdr.name, hdr.namelen as usize, from.as_deref())?
+                as u32;
+    } else {
+        hdr.namelen = 0;
+    }
+
+    copy_to_user(msghdr_ptr, &hdr)?;
     Ok(received)
 }
 
-/// Write SCM_RIGHTS fds into the user-space msg_control buffer as a cmsghdr.
-fn write_scm_rights(control_ptr: usize, control_len: usize, fds: &[u32], msghdr_ptr: usize) {
-    const SOL_SOCKET: u32 = 1;
-    const SCM_RIGHTS: u32 = 1;
-    const CMSGHDR_SIZE: usize = 16;
-
-    let needed = CMSGHDR_SIZE + fds.len() * 4;
-    if needed > control_len {
-        return;
-    }
-
-    // SAFETY: control_ptr was validated by caller via validate_user_buffer.
-    unsafe {
-        let base = control_ptr as *mut u32;
-        // cmsg_len
-        *base = needed as u32;
-        // cmsg_level = SOL_SOCKET
-        *base.add(1) = SOL_SOCKET;
-        // cmsg_type = SCM_RIGHTS
-        *base.add(2) = SCM_RIGHTS;
-        // Write fd array
-        let fd_base = (control_ptr + CMSGHDR_SIZE) as *mut i32;
-        for (i, &fd) in fds.iter().enumerate() {
-            *fd_base.add(i) = fd as i32;
+/// Install received files in the caller's file table and describe them in
+/// the msg_control buffer. Returns the control bytes used and whether any
+/// descriptor was dropped for lack of room.
+#[cfg(feature = "net")]
+fn install_scm_rights(
+    control_ptr: usize,
+    control_len: usize,
+    scm: crate::net::unix_socket::ScmRights,
+) -> Result<(usize, bool), SyscallError> {
+    let room = if control_ptr != 0 && control_len > CMSGHDR_SIZE {
+        (control_len - CMSGHDR_SIZE) / 4
+    } else {
+        0
+    };
+    let total = scm.files.len();
+    if room == 0 {
+        return Ok((0, total > 0));
+    }
+
+    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let mut fds = alloc::vec::Vec::new();
+    {
+        let file_table = process.file_table.lock();
+        for file in scm.files.into_iter().take(room) {
+            match file_table.open(file) {
+                Ok(fd) => fds.push(fd as i32),
+                Err(_) => break,
+            }
         }
     }
+    let truncated = fds.len() < total;
 
-    // Update msg_controllen in the msghdr to reflect actual data written
-    // SAFETY: msghdr_ptr was validated by caller.
-    unsafe {
-        let controllen_ptr = (msghdr_ptr as *mut usize).add(5);
-        *controllen_ptr = needed;
+    let cmsg_len = CMSGHDR_SIZE + fds.len() * 4;
+    let mut cmsg = alloc::vec::Vec::with_capacity(cmsg_len);
+    cmsg.extend_from_slice(&cmsg_len.to_ne_bytes());
+    cmsg.extend_from_slice(&SOL_SOCKET.to_ne_bytes());
+    cmsg.extend_from_slice(&SCM_RIGHTS.to_ne_bytes());
+    for fd in &fds {
+        cmsg.extend_from_slice(&fd.to_ne_bytes());
     }
+    // cmsg_len fits control_len because fds.len() <= room
+    copy_bytes_to_user(control_ptr, &cmsg)?;
+
+    // CMSG_SPACE, capped at the buffer
+    Ok((((cmsg_len + 7) & !7).min(control_len), truncated))
 }
 
 /// IPC send system call
@@ -1951,7 +1895,7 @@ fn sys_ipc_send(
     _flags: usize,
 ) -> SyscallResult {
     // Validate user-space pointer bounds
-    validate_user_pointer(msg_ptr, msg_size)?;
+    user_range(msg_ptr, msg_size)?;
 
     // Get current process's capability space
     let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -1962,53 +1906,36 @@ fn sys_ipc_send(
     // Convert capability value to token
     let cap_token = crate::cap::CapabilityToken::from_u64(capability as u64);
 
-    // Check send permission
-    if let Err(e) = crate::cap::ipc_integration::check_send_permission(cap_token, &cap_space) {
-        return Err(e.into());
-    }
+    // Check send permission and resolve the endpoint the capability is bound to
+    let endpoint = crate::cap::ipc_integration::resolve_endpoint(
+        cap_token,
+        crate::cap::ipc_integration::IpcRights::SEND,
+        &cap_space,
+    )?;
+    drop(cap_space);
 
     // Check if this is a small message (fast path)
     let message = if msg_size <= core::mem::size_of::<SmallMessage>() {
         // Fast path for small messages
-        // SAFETY: msg_ptr was validated as non-zero above. The caller passes
-        // a user-space pointer to a SmallMessage. We read the entire struct
-        // by value. SmallMessage is Copy and repr(C), so the read is valid
-        // if the pointer is properly aligned and points to valid memory.
-        unsafe {
-            let small_msg = *(msg_ptr as *const SmallMessage);
-            Message::Small(small_msg)
-        }
+        Message::Small(copy_from_user::<SmallMessage>(msg_ptr)?)
     } else {
-        // Large message path
-        // SAFETY: msg_ptr is non-zero (checked above) and msg_size > 0.
-        // The user-space buffer at msg_ptr is expected to contain msg_size
-        // bytes. We create a slice reference for the message data. The
-        // LargeMessage is constructed with the user-space address for
-        // later zero-copy transfer.
-        unsafe {
-            let _msg_slice = core::slice::from_raw_parts(msg_ptr as *const u8, msg_size);
-
-            // For now, create a large message with basic header
-            // In a real implementation, this would handle shared memory regions
-            let large_msg = crate::ipc::LargeMessage {
-                header: crate::ipc::message::MessageHeader::new(
-                    capability as u64,
-                    0,
-                    msg_size as u64,
-                ),
-                memory_region: crate::ipc::message::MemoryRegion::new(
-                    msg_ptr as u64,
-                    msg_size as u64,
-                ),
-                inline_data: [0; crate::ipc::message::SMALL_MESSAGE_MAX_SIZE],
-            };
+        // Large message path: the payload stays in user space and is
+        // referenced by address for later zero-copy transfer.
+        access_ok(msg_ptr, msg_size, Access::Read)?;
 
-            Message::Large(large_msg)
-        }
+        // For now, create a large message with basic header
+        // In a real implementation, this would handle shared memory regions
+        let large_msg = crate::ipc::LargeMessage {
+            header: crate::ipc::message::MessageHeader::new(capability as u64, 0, msg_size as u64),
+            memory_region: crate::ipc::message::MemoryRegion::new(msg_ptr as u64, msg_size as u64),
+            inline_data: [0; crate::ipc::message::SMALL_MESSAGE_MAX_SIZE],
+        };
+
+        Message::Large(large_msg)
     };
 
     // Perform the actual send using the IPC sync module
-    match sync_send(message, capability as u64) {
+    match sync_send(message, endpoint) {
         Ok(()) => Ok(0),
         Err(e) => Err(e.into()),
     }
@@ -2021,7 +1948,7 @@ fn sys_ipc_send(
 /// - buffer: Buffer to receive message into
 fn sys_ipc_receive(endpoint: usize, buffer: usize) -> SyscallResult {
     // Validate receive buffer can hold at least a SmallMessage
-    validate_user_buffer(buffer, core::mem::size_of::<SmallMessage>())?;
+    access_ok(buffer, core::mem::size_of::<SmallMessage>(), Access::Write)?;
 
     // Get current process's capability space
     let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -2032,58 +1959,26 @@ fn sys_ipc_receive(endpoint: usize, buffer: usize) -> SyscallResult {
     // Convert endpoint to capability token
     let cap_token = crate::cap::CapabilityToken::from_u64(endpoint as u64);
 
-    // Check receive permission
-    if let Err(e) = crate::cap::ipc_integration::check_receive_permission(cap_token, &cap_space) {
-        return Err(e.into());
-    }
+    // Check receive permission and resolve the bound endpoint
+    let endpoint_id = crate::cap::ipc_integration::resolve_endpoint(
+        cap_token,
+        crate::cap::ipc_integration::IpcRights::RECEIVE,
+        &cap_space,
+    )?;
+    drop(cap_space);
 
     // Receive message using IPC sync module
-    match sync_receive(endpoint as u64) {
-        Ok(message) => {
-            // Copy message to user buffer
-            // SAFETY: buffer was validated as non-zero above. We write the
-            // received message to the user-space buffer. For SmallMessage,
-            // we write the struct directly. For LargeMessage, we copy the
-            // header and data. The caller is responsible for providing a
-            // buffer large enough to hold the message.
-            unsafe {
-                match message {
-                    Message::Small(small_msg) => {
-                        // Copy small message to buffer
-                        let dst = buffer as *mut SmallMessage;
-                        *dst = small_msg;
-                        Ok(core::mem::size_of::<SmallMessage>())
-                    }
-                    Message::Large(large_msg) => {
-                        // For large messages, copy the header and setup shared memory
-                        // In a real implementation, this would handle memory mapping
-                        let header_size =
-                            core::mem::size_of::<crate::ipc::message::MessageHeader>();
-                        let dst = buffer as *mut u8;
-
-                        // Copy header
-                        core::ptr::copy_nonoverlapping(
-                            &large_msg.header as *const _ as *const u8,
-                            dst,
-                            header_size,
-                        );
-
-                        // Copy data if it fits
-                        if large_msg.memory_region.size > 0
-                            && large_msg.memory_region.base_addr != 0
-                        {
-                            let data_dst = dst.add(header_size);
-                            core::ptr::copy_nonoverlapping(
-                                large_msg.memory_region.base_addr as *const u8,
-                                data_dst,
-                                large_msg.memory_region.size as usize,
-                            );
-                        }
-
-                        Ok(header_size + large_msg.memory_region.size as usize)
-                    }
-                }
-            }
+    match sync_receive(endpoint_id) {
+        // Copy message to user buffer. The caller is responsible for
+        // providing a buffer large enough to hold a large message.
+        Ok(Message::Small(small_msg)) => {
+            copy_to_user(buffer, &small_msg)?;
+            Ok(core::mem::size_of::<SmallMessage>())
+        }
+        // For large messages, copy the header and data. In a real
+        // implementation, this would handle memory mapping.
+        Ok(Message::Large(large_msg)) => {
+            copy_large_message_out(buffer, &large_msg, large_msg.memory_region.size as usize)
         }
         Err(e) => Err(e.into()),
     }
@@ -2101,18 +1996,27 @@ fn sys_ipc_call(
     if send_size == 0 || recv_size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(send_msg, send_size)?;
-    validate_user_buffer(recv_buf, recv_size)?;
+    access_ok(send_msg, send_size, Access::Read)?;
+    access_ok(recv_buf, recv_size, Access::Write)?;
+
+    // A call is a send followed by a receive on the caller's reply slot, so
+    // only SEND rights on the target endpoint are required
+    let endpoint = {
+        let current_process =
+            crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+        let real_process = crate::process::table::get_process(current_process.pid)
+            .ok_or(SyscallError::InvalidState)?;
+        let cap_space = real_process.capability_space.lock();
+        crate::cap::ipc_integration::resolve_endpoint(
+            crate::cap::CapabilityToken::from_u64(capability as u64),
+            crate::cap::ipc_integration::IpcRights::SEND,
+            &cap_space,
+        )?
+    };
 
     // Create message from user buffer
     let message = if send_size <= core::mem::size_of::<SmallMessage>() {
-        // SAFETY: send_msg was validated as non-zero above and send_size
-        // fits within SmallMessage. The pointer cast reads the struct by
-        // value. SmallMessage is Copy and repr(C).
-        unsafe {
-            let small_msg = *(send_msg as *const SmallMessage);
-            Message::Small(small_msg)
-        }
+        Message::Small(copy_from_user::<SmallMessage>(send_msg)?)
     } else {
         // Create large message
         let large_msg = crate::ipc::LargeMessage {
@@ -2127,79 +2031,58 @@ fn sys_ipc_call(
     };
 
     // Perform synchronous call
-    match sync_call(message, capability as u64) {
-        Ok(reply) => {
-            // Copy reply to receive buffer
-            // SAFETY: recv_buf was validated as non-zero and recv_size > 0
-            // above. We write the reply message to the user buffer, checking
-            // that recv_size is large enough for SmallMessage or the header.
-            // The caller must provide adequately sized buffers.
-            unsafe {
-                match reply {
-                    Message::Small(small_msg) => {
-                        if recv_size >= core::mem::size_of::<SmallMessage>() {
-                            let dst = recv_buf as *mut SmallMessage;
-                            *dst = small_msg;
-                            Ok(core::mem::size_of::<SmallMessage>())
-                        } else {
-                            Err(SyscallError::InvalidArgument)
-                        }
-                    }
-                    Message::Large(large_msg) => {
-                        let header_size =
-                            core::mem::size_of::<crate::ipc::message::MessageHeader>();
-                        if recv_size >= header_size {
-                            let dst = recv_buf as *mut u8;
-
-                            // Copy header
-                            core::ptr::copy_nonoverlapping(
-                                &large_msg.header as *const _ as *const u8,
-                                dst,
-                                header_size,
-                            );
-
-                            // Copy data
-                            let data_to_copy = core::cmp::min(
-                                large_msg.memory_region.size as usize,
-                                recv_size - header_size,
-                            );
-                            if data_to_copy > 0 && large_msg.memory_region.base_addr != 0 {
-                                let data_dst = dst.add(header_size);
-                                core::ptr::copy_nonoverlapping(
-                                    large_msg.memory_region.base_addr as *const u8,
-                                    data_dst,
-                                    data_to_copy,
-                                );
-                            }
-
-                            Ok(header_size + data_to_copy)
-                        } else {
-                            Err(SyscallError::InvalidArgument)
-                        }
-                    }
-                }
+    match sync_call(message, endpoint) {
+        // Copy reply to receive buffer, checking that recv_size is large
+        // enough for a SmallMessage or the large message header
+        Ok(Message::Small(small_msg)) => {
+            if recv_size < core::mem::size_of::<SmallMessage>() {
+                return Err(SyscallError::InvalidArgument);
+            }
+            copy_to_user(recv_buf, &small_msg)?;
+            Ok(core::mem::size_of::<SmallMessage>())
+        }
+        Ok(Message::Large(large_msg)) => {
+            let header_size = core::mem::size_of::<crate::ipc::message::MessageHeader>();
+            if recv_size < header_size {
+                return Err(SyscallError::InvalidArgument);
             }
+            let data_len = core::cmp::min(
+                large_msg.memory_region.size as usize,
+                recv_size - header_size,
+            );
+            copy_large_message_out(recv_buf, &large_msg, data_len)
         }
         Err(e) => Err(e.into()),
     }
 }
 
+/// Copy a large message's header, followed by the first `data_len` bytes of
+/// its payload, to the user buffer at `dst`.
+fn copy_large_message_out(
+    dst: usize,
+    msg: &crate::ipc::LargeMessage,
+    data_len: usize,
+) -> SyscallResult {
+    let header_size = core::mem::size_of::<crate::ipc::message::MessageHeader>();
+    copy_to_user(dst, &msg.header)?;
+    if data_len > 0 && msg.memory_region.base_addr != 0 {
+        let data = copy_slice_from_user::<u8>(msg.memory_region.base_addr as usize, data_len)?;
+        copy_bytes_to_user(dst + header_size, &data)?;
+    }
+    Ok(header_size + data_len)
+}
+
 /// IPC reply to a previous call
 fn sys_ipc_reply(caller: usize, msg_ptr: usize, msg_size: usize) -> SyscallResult {
     // Validate reply message buffer
     if msg_size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(msg_ptr, msg_size)?;
+    access_ok(msg_ptr, msg_size, Access::Read)?;
 
     // Create reply message
     let message = if msg_size <= core::mem::size_of::<SmallMessage>() {
-        // SAFETY: msg_ptr was validated as non-zero above and msg_size fits
-        // within SmallMessage. The pointer cast reads a Copy/repr(C) struct.
-        unsafe {
-            let small_msg = *(msg_ptr as *const SmallMessage);
-            Message::Small(small_msg)
-        }
+        Message::Small(copy_from_user::<SmallMessage>(msg_ptr)?)
     } else {
         let large_msg = crate::ipc::LargeMessage {
             header: crate::ipc::message::MessageHeader::new(0, 0, msg_size as u64),
@@ -2238,18 +2121,141 @@ fn sys_ipc_create_endpoint(_permissions: usize) -> SyscallResult {
     }
 }
 
-/// Bind endpoint to a name
-fn sys_ipc_bind_endpoint(endpoint_id: usize, name_ptr: usize) -> SyscallResult {
-    // Validate name pointer is in user space (at least 1 byte for a string)
-    validate_user_string_ptr(name_ptr)?;
+/// Bind endpoint to a service name in the caller's namespace
+///
+/// A sandboxed caller may only bind names its profile lists.
+///
+/// # Arguments
+/// - capability: Endpoint capability token (requires BIND rights)
+/// - name_ptr: Pointer to null-terminated service name
+fn sys_ipc_bind_endpoint(capability: usize, name_ptr: usize) -> SyscallResult {
+    let name = read_user_name(name_ptr, crate::ipc::namespace::SERVICE_NAME_MAX)?;
+
+    let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    crate::security::sandbox::check_service(current_process, &name)
+        .map_err(|_| SyscallError::PermissionDenied)?;
+    let endpoint = {
+        let cap_space = current_process.capability_space.lock();
+        crate::cap::ipc_integration::resolve_endpoint(
+            crate::cap::CapabilityToken::from_u64(capability as u64),
+            crate::cap::ipc_integration::IpcRights::BIND,
+            &cap_space,
+        )?
+    };
+
+    crate::ipc::namespace::bind_name(current_process.pid, &name, endpoint)?;
+    Ok(0)
+}
+
+/// Look up a service name in the caller's namespace
+///
+/// On success a new send-only capability for the service endpoint is
+/// inserted into the caller's capability space and its token returned.
+/// Names bound outside the caller's namespace, and names its sandbox
+/// profile does not list, report `ResourceNotFound`, exactly like names
+/// that do not exist.
+///
+/// # Arguments
+/// - name_ptr: Pointer to null-terminated service name
+fn sys_ipc_lookup_endpoint(name_ptr: usize) -> SyscallResult {
+    let name = read_user_name(name_ptr, crate::ipc::namespace::SERVICE_NAME_MAX)?;
+
+    let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    crate::security::sandbox::check_service(current_process, &name)
+        .map_err(|_| SyscallError::ResourceNotFound)?;
+    let endpoint = crate::ipc::namespace::lookup_name(current_process.pid, &name)?;
+
+    let cap_space = current_process.capability_space.lock();
+    let cap = crate::cap::ipc_integration::mint_endpoint_capability(
+        endpoint,
+        crate::cap::ipc_integration::IpcRights::SEND,
+        &cap_space,
+    )?;
+    Ok(cap.to_u64() as usize)
+}
+
+/// Create an IPC namespace supervised by the caller
+///
+/// The namespace starts out empty and is derived from the caller's own
+/// namespace, so a sandboxed supervisor can only nest further sandboxes.
+/// Returns the namespace ID.
+fn sys_ipc_namespace_create() -> SyscallResult {
+    let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let ns = crate::ipc::namespace::create_supervised(current_process.pid)?;
+    Ok(ns as usize)
+}
+
+/// Move a process into a namespace the caller supervises
+///
+/// Requires root or the MODIFY right on a Process capability for `pid`.
+/// The process must be in the caller's namespace or one it supervises, and
+/// `ns` must be one of those too.
+///
+/// # Arguments
+/// - pid: Process to move
+/// - ns: Namespace ID from `SYS_IPC_NAMESPACE_CREATE`, or the caller's own
+///   namespace to release the process from a sandbox
+fn sys_ipc_namespace_assign(pid: usize, ns: usize) -> SyscallResult {
+    let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let target = crate::process::ProcessId(pid as u64);
+    crate::process::table::get_process(target).ok_or(SyscallError::ResourceNotFound)?;
+    if current_process.euid() != 0 {
+        let cap_space = current_process.capability_space.lock();
+        if !holds_process_capability(&cap_space, target, crate::cap::Rights::MODIFY) {
+            return Err(SyscallError::PermissionDenied);
+        }
+    }
+
+    crate::ipc::namespace::assign_supervised(current_process.pid, target, ns as u64)?;
+    Ok(0)
+}
+
+/// Export a service into a namespace the caller supervises
+///
+/// The name is resolved in the caller's namespace and `capability` must be
+/// an endpoint capability with SEND rights for the endpoint it is bound
+/// to: a supervisor can only pass on services it can reach itself.
+///
+/// # Arguments
+/// - capability: Endpoint capability token for the service (SEND rights)
+/// - name_ptr: Pointer to null-terminated service name
+/// - ns: Namespace ID from `SYS_IPC_NAMESPACE_CREATE`
+fn sys_ipc_namespace_export(capability: usize, name_ptr: usize, ns: usize) -> SyscallResult {
+    let name = read_user_name(name_ptr, crate::ipc::namespace::SERVICE_NAME_MAX)?;
+
+    let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    crate::security::sandbox::check_service(current_process, &name)
+        .map_err(|_| SyscallError::ResourceNotFound)?;
+    let endpoint = {
+        let cap_space = current_process.capability_space.lock();
+        crate::cap::ipc_integration::resolve_endpoint(
+            crate::cap::CapabilityToken::from_u64(capability as u64),
+            crate::cap::ipc_integration::IpcRights::SEND,
+            &cap_space,
+        )?
+    };
+
+    crate::ipc::namespace::export_supervised(current_process.pid, &name, endpoint, ns as u64)?;
+    Ok(0)
+}
 
-    // For now, just validate the endpoint exists
-    // In a real implementation, this would register the endpoint with a name
-    // service
-    match crate::ipc::registry::lookup_endpoint(endpoint_id as u64) {
-        Ok(_) => Ok(0),
-        Err(_) => Err(SyscallError::ResourceNotFound),
+/// Whether `cap_space` holds a Process capability for `pid` with `rights`.
+fn holds_process_capability(
+    cap_space: &crate::cap::CapabilitySpace,
+    pid: crate::process::ProcessId,
+    rights: crate::cap::Rights,
+) -> bool {
+    let mut found = false;
+    #[cfg(feature = "alloc")]
+    {
+        let _ = cap_space.iter_capabilities(|entry| {
+            if let crate::cap::ObjectRef::Process { pid: cap_pid } = &entry.object {
+                found = *cap_pid == pid && entry.rights.contains(rights);
+            }
+            !found
+        });
     }
+    found
 }
 
 /// Share memory region via IPC
@@ -2265,7 +2271,7 @@ fn sys_ipc_share_memory(
     if size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(addr, size)?;
+    user_range(addr, size)?;
 
     // Get current process and capability space
     let current_process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -2380,317 +2386,13 @@ fn sys_ipc_map_memory(capability: usize, addr_hint: usize, flags: usize) -> Sysc
     Ok(vaddr)
 }
 
-impl TryFrom<usize> for Syscall {
-    type Error = ();
-
-    fn try_from(value: usize) -> Result<Self, Self::Error> {
-        match value {
-            // IPC system calls
-            0 => Ok(Syscall::IpcSend),
-            1 => Ok(Syscall::IpcReceive),
-            2 => Ok(Syscall::IpcCall),
-            3 => Ok(Syscall::IpcReply),
-            4 => Ok(Syscall::IpcCreateEndpoint),
-            5 => Ok(Syscall::IpcBindEndpoint),
-            6 => Ok(Syscall::IpcShareMemory),
-            7 => Ok(Syscall::IpcMapMemory),
-
-            // Process management
-            10 => Ok(Syscall::ProcessYield),
-            11 => Ok(Syscall::ProcessExit),
-            12 => Ok(Syscall::ProcessFork),
-            13 => Ok(Syscall::ProcessExec),
-            14 => Ok(Syscall::ProcessWait),
-            15 => Ok(Syscall::ProcessGetPid),
-            16 => Ok(Syscall::ProcessGetPPid),
-            17 => Ok(Syscall::ProcessSetPriority),
-            18 => Ok(Syscall::ProcessGetPriority),
-
-            // Memory management
-            20 => Ok(Syscall::MemoryMap),
-            21 => Ok(Syscall::MemoryUnmap),
-            22 => Ok(Syscall::MemoryProtect),
-            23 => Ok(Syscall::MemoryBrk),
-
-            // Capability management
-            30 => Ok(Syscall::CapabilityGrant),
-            31 => Ok(Syscall::CapabilityRevoke),
-
-            // Thread management
-            40 => Ok(Syscall::ThreadCreate),
-            41 => Ok(Syscall::ThreadExit),
-            42 => Ok(Syscall::ThreadJoin),
-            43 => Ok(Syscall::ThreadGetTid),
-            44 => Ok(Syscall::ThreadSetAffinity),
-            45 => Ok(Syscall::ThreadGetAffinity),
-            46 => Ok(Syscall::ThreadClone),
-
-            // Filesystem operations
-            50 => Ok(Syscall::FileOpen),
-            51 => Ok(Syscall::FileClose),
-            52 => Ok(Syscall::FileRead),
-            53 => Ok(Syscall::FileWrite),
-            54 => Ok(Syscall::FileSeek),
-            55 => Ok(Syscall::FileStat),
-            56 => Ok(Syscall::FileTruncate),
-            57 => Ok(Syscall::FileDup),
-            58 => Ok(Syscall::FileDup2),
-            59 => Ok(Syscall::FilePipe),
-
-            // Directory operations
-            60 => Ok(Syscall::DirMkdir),
-            61 => Ok(Syscall::DirRmdir),
-            62 => Ok(Syscall::DirOpendir),
-            63 => Ok(Syscall::DirReaddir),
-            64 => Ok(Syscall::DirClosedir),
-            65 => Ok(Syscall::FilePipe2),
-            66 => Ok(Syscall::FileDup3),
-
-            // Filesystem management
-            70 => Ok(Syscall::FsMount),
-            71 => Ok(Syscall::FsUnmount),
-            72 => Ok(Syscall::FsSync),
-            73 => Ok(Syscall::FsFsync),
-
-            // Kernel information
-            80 => Ok(Syscall::KernelGetInfo),
-
-            // Package management
-            90 => Ok(Syscall::PkgInstall),
-            91 => Ok(Syscall::PkgRemove),
-            92 => Ok(Syscall::PkgQuery),
-            93 => Ok(Syscall::PkgList),
-            94 => Ok(Syscall::PkgUpdate),
-
-            // Time management
-            100 => Ok(Syscall::TimeGetUptime),
-            101 => Ok(Syscall::TimeCreateTimer),
-            102 => Ok(Syscall::TimeCancelTimer),
-
-            // Extended process operations
-            110 => Ok(Syscall::ProcessGetcwd),
-            111 => Ok(Syscall::ProcessChdir),
-            112 => Ok(Syscall::FileIoctl),
-            113 => Ok(Syscall::ProcessKill),
-
-            // Signal management
-            120 => Ok(Syscall::SigAction),
-            121 => Ok(Syscall::SigProcmask),
-            122 => Ok(Syscall::SigSuspend),
-            123 => Ok(Syscall::SigReturn),
-
-            // Debug / tracing
-            140 => Ok(Syscall::Ptrace),
-
-            // POSIX time syscalls
-            160 => Ok(Syscall::ClockGettime),
-            161 => Ok(Syscall::ClockGetres),
-            162 => Ok(Syscall::Nanosleep),
-            163 => Ok(Syscall::Gettimeofday),
-
-            // Identity syscalls
-            170 => Ok(Syscall::Getuid),
-            171 => Ok(Syscall::Geteuid),
-            172 => Ok(Syscall::Getgid),
-            173 => Ok(Syscall::Getegid),
-            174 => Ok(Syscall::Setuid),
-            175 => Ok(Syscall::Setgid),
-
-            // Process group / session syscalls
-            176 => Ok(Syscall::Setpgid),
-            177 => Ok(Syscall::Getpgid),
-            178 => Ok(Syscall::Getpgrp),
-            179 => Ok(Syscall::Setsid),
-            180 => Ok(Syscall::Getsid),
-
-            // Scatter/gather I/O
-            183 => Ok(Syscall::Readv),
-            184 => Ok(Syscall::Writev),
-
-            // Extended filesystem operations
-            150 => Ok(Syscall::FileStatPath),
-            151 => Ok(Syscall::FileLstat),
-            152 => Ok(Syscall::FileReadlink),
-            153 => Ok(Syscall::FileAccess),
-            154 => Ok(Syscall::FileRename),
-            155 => Ok(Syscall::FileLink),
-            156 => Ok(Syscall::FileSymlink),
-            157 => Ok(Syscall::FileUnlink),
-            158 => Ok(Syscall::FileFcntl),
-
-            // Self-hosting filesystem ops
-            185 => Ok(Syscall::FileChmod),
-            186 => Ok(Syscall::FileFchmod),
-            187 => Ok(Syscall::ProcessUmask),
-            188 => Ok(Syscall::FileTruncatePath),
-            189 => Ok(Syscall::FilePoll),
-            190 => Ok(Syscall::FileOpenat),
-            191 => Ok(Syscall::FileFstatat),
-            192 => Ok(Syscall::FileUnlinkat),
-            193 => Ok(Syscall::FileMkdirat),
-            194 => Ok(Syscall::FileRenameat),
-            195 => Ok(Syscall::FilePread),
-            196 => Ok(Syscall::FilePwrite),
-            197 => Ok(Syscall::FileChown),
-            198 => Ok(Syscall::FileFchown),
-            199 => Ok(Syscall::FileMknod),
-            200 => Ok(Syscall::FileSelect),
-            201 => Ok(Syscall::FutexWait),
-            202 => Ok(Syscall::FutexWake),
-            203 => Ok(Syscall::ArchPrctl),
-            204 => Ok(Syscall::ProcessUname),
-            205 => Ok(Syscall::ProcessGetenv),
-
-            // POSIX shared memory
-            210 => Ok(Syscall::ShmOpen),
-            211 => Ok(Syscall::ShmUnlink),
-            212 => Ok(Syscall::ShmTruncate),
-
-            // Socket operations
-            220 => Ok(Syscall::SocketCreate),
-            221 => Ok(Syscall::SocketBind),
-            222 => Ok(Syscall::SocketListen),
-            223 => Ok(Syscall::SocketConnect),
-            224 => Ok(Syscall::SocketAccept),
-            225 => Ok(Syscall::SocketSend),
-            226 => Ok(Syscall::SocketRecv),
-            227 => Ok(Syscall::SocketClose),
-            228 => Ok(Syscall::SocketPair),
-
-            // Graphics / framebuffer (Phase 6)
-            230 => Ok(Syscall::FbGetInfo),
-            231 => Ok(Syscall::FbMap),
-            232 => Ok(Syscall::InputPoll),
-            233 => Ok(Syscall::InputRead),
-            234 => Ok(Syscall::FbSwap),
-
-            // Wayland compositor (Phase 6)
-            240 => Ok(Syscall::WlConnect),
-            241 => Ok(Syscall::WlDisconnect),
-            242 => Ok(Syscall::WlSendMessage),
-            243 => Ok(Syscall::WlRecvMessage),
-            244 => Ok(Syscall::WlCreateShmPool),
-            245 => Ok(Syscall::WlCreateSurface),
-            246 => Ok(Syscall::WlCommitSurface),
-            247 => Ok(Syscall::WlGetEvents),
-
-            // Network extensions (Phase 6)
-            250 => Ok(Syscall::NetSendTo),
-            251 => Ok(Syscall::NetRecvFrom),
-            252 => Ok(Syscall::NetGetSockName),
-            253 => Ok(Syscall::NetGetPeerName),
-            254 => Ok(Syscall::NetSetSockOpt),
-            255 => Ok(Syscall::NetGetSockOpt),
-
-            // Resource limits (Phase 6.5)
-            260 => Ok(Syscall::GetRlimit),
-            261 => Ok(Syscall::SetRlimit),
-
-            // epoll I/O multiplexing (Phase 6.5)
-            262 => Ok(Syscall::EpollCreate),
-            263 => Ok(Syscall::EpollCtl),
-            264 => Ok(Syscall::EpollWait),
-
-            // Process groups / sessions (Phase 6.5)
-            270 => Ok(Syscall::SetPgid),
-            271 => Ok(Syscall::GetPgid),
-            272 => Ok(Syscall::SetSid),
-            273 => Ok(Syscall::GetSid),
-            274 => Ok(Syscall::TcSetPgrp),
-            275 => Ok(Syscall::TcGetPgrp),
-
-            // PTY (Phase 6.5)
-            280 => Ok(Syscall::OpenPty),
-            281 => Ok(Syscall::GrantPty),
-            282 => Ok(Syscall::UnlockPty),
-            283 => Ok(Syscall::PtsName),
-
-            // Filesystem extensions (Phase 6.5)
-            290 => Ok(Syscall::Link),
-            291 => Ok(Syscall::Symlink),
-            292 => Ok(Syscall::Readlink),
-            293 => Ok(Syscall::Lstat),
-            294 => Ok(Syscall::Fchmod),
-            295 => Ok(Syscall::Fchown),
-            296 => Ok(Syscall::Umask),
-            297 => Ok(Syscall::Access),
-
-            // Poll/fcntl (Phase 6.5)
-            300 => Ok(Syscall::Poll),
-            301 => Ok(Syscall::Fcntl),
-
-            // Threading (Phase 6.5)
-            310 => Ok(Syscall::Clone),
-            311 => Ok(Syscall::Futex),
-
-            // Audio (Phase 7)
-            320 => Ok(Syscall::AudioOpen),
-            321 => Ok(Syscall::AudioClose),
-            322 => Ok(Syscall::AudioWrite),
-            323 => Ok(Syscall::AudioSetVolume),
-            324 => Ok(Syscall::AudioGetInfo),
-            325 => Ok(Syscall::AudioStart),
-            326 => Ok(Syscall::AudioStop),
-            327 => Ok(Syscall::AudioPause),
-
-            // Event/timer notification fds (KDE/Wayland infrastructure)
-            330 => Ok(Syscall::Getrandom),
-            331 => Ok(Syscall::EventfdCreate),
-            332 => Ok(Syscall::EventfdRead),
-            333 => Ok(Syscall::EventfdWrite),
-            334 => Ok(Syscall::TimerfdCreate),
-            335 => Ok(Syscall::TimerfdSettime),
-            336 => Ok(Syscall::TimerfdGettime),
-            337 => Ok(Syscall::SignalfdCreate),
-            338 => Ok(Syscall::SendMsg),
-            339 => Ok(Syscall::RecvMsg),
-
-            // musl libc compatibility
-            340 => Ok(Syscall::Getdents64),
-            341 => Ok(Syscall::Prlimit64),
-            342 => Ok(Syscall::InotifyInit1),
-            343 => Ok(Syscall::InotifyAddWatch),
-            344 => Ok(Syscall::InotifyRmWatch),
-            345 => Ok(Syscall::Madvise),
-            346 => Ok(Syscall::Fchmodat),
-            347 => Ok(Syscall::Fchownat),
-            348 => Ok(Syscall::Linkat),
-            349 => Ok(Syscall::Symlinkat),
-            350 => Ok(Syscall::Readlinkat),
-            351 => Ok(Syscall::MemfdCreate),
-            352 => Ok(Syscall::SetTidAddress),
-            353 => Ok(Syscall::SetRobustList),
-            354 => Ok(Syscall::ClockNanosleep),
-
-            _ => Err(()),
-        }
-    }
-}
-
 // ---------------------------------------------------------------------------
 // POSIX Shared Memory syscall handlers
 // ---------------------------------------------------------------------------
 
 /// Read a null-terminated name string from user space (for shm/socket paths).
 fn read_user_name(ptr: usize, max_len: usize) -> Result<alloc::string::String, SyscallError> {
-    validate_user_string_ptr(ptr)?;
-    // SAFETY: ptr was validated as non-null and in user-space.
-    let bytes = unsafe {
-        let mut buf = alloc::vec::Vec::new();
-        let mut p = ptr as *const u8;
-        for _ in 0..max_len {
-            let byte = *p;
-            if byte == 0 {
-                break;
-            }
-            buf.push(byte);
-            p = p.add(1);
-        }
-        buf
-    };
-    core::str::from_utf8(&bytes)
-        .map(alloc::string::String::from)
-        .map_err(|_| SyscallError::InvalidArgument)
+    strncpy_from_user(ptr, max_len)
 }
 
 /// SYS_SHM_OPEN: Create or open a named shared memory object.
@@ -2714,7 +2416,7 @@ fn sys_shm_open(name_ptr: usize, flags: usize, _mode: usize) -> SyscallResult {
 
     crate::ipc::posix_shm::shm_open(&name, shm_flags, pid)
         .map(|id| id as usize)
-        .map_err(|_| SyscallError::InvalidState)
+        .map_err(map_kernel_error)
 }
 
 /// SYS_SHM_UNLINK: Remove a named shared memory object.
@@ -2727,7 +2429,7 @@ fn sys_shm_unlink(name_ptr: usize, _name_len: usize) -> SyscallResult {
 
     crate::ipc::posix_shm::shm_unlink(&name)
         .map(|()| 0)
-        .map_err(|_| SyscallError::ResourceNotFound)
+        .map_err(map_kernel_error)
 }
 
 /// SYS_SHM_TRUNCATE: Set the size of a shared memory object.
@@ -2741,7 +2443,23 @@ fn sys_shm_truncate(name_ptr: usize, _name_len: usize, size: usize) -> SyscallRe
 
     crate::ipc::posix_shm::shm_truncate(&name, size)
         .map(|()| 0)
-        .map_err(|_| SyscallError::OutOfMemory)
+        .map_err(map_kernel_error)
+}
+
+/// SYS_SHM_CLOSE: Drop a reference taken by `SYS_SHM_OPEN`.
+///
+/// An object that has been unlinked is freed when its last reference is
+/// closed.
+///
+/// # Arguments
+/// - name_ptr: user-space pointer to null-terminated name
+/// - _name_len: length hint (unused)
+fn sys_shm_close(name_ptr: usize, _name_len: usize) -> SyscallResult {
+    let name = read_user_name(name_ptr, crate::ipc::posix_shm::SHM_NAME_MAX)?;
+
+    crate::ipc::posix_shm::shm_close(&name)
+        .map(|()| 0)
+        .map_err(map_kernel_error)
 }
 
 // ---------------------------------------------------------------------------
@@ -2749,60 +2467,218 @@ fn sys_shm_truncate(name_ptr: usize, _name_len: usize, size: usize) -> SyscallRe
 // ---------------------------------------------------------------------------
 
 /// Socket domain constants matching POSIX/libc.
+#[cfg(feature = "net")]
 const AF_UNIX: usize = 1;
+#[cfg(feature = "net")]
 const AF_INET: usize = 2;
 
 /// Socket type constants matching POSIX/libc.
+#[cfg(feature = "net")]
 const SOCK_STREAM: usize = 1;
+#[cfg(feature = "net")]
 const SOCK_DGRAM: usize = 2;
 
-/// High-bit flag to distinguish INET socket IDs from Unix socket IDs.
-/// Applied to socket IDs returned by `sys_socket_create` for AF_INET sockets.
-const INET_SOCKET_FLAG: usize = 0x4000_0000;
-
-/// Check if a socket ID refers to an INET socket.
-#[inline]
-fn is_inet_socket(id: usize) -> bool {
-    id & INET_SOCKET_FLAG != 0
-}
-
-/// Strip the INET flag to get the raw socket table ID.
-#[inline]
-fn inet_socket_id(id: usize) -> usize {
-    id & !INET_SOCKET_FLAG
-}
+/// Mask for the socket type in the `type` argument of socket() and
+/// socketpair(); the bits above it are SOCK_NONBLOCK and SOCK_CLOEXEC.
+#[cfg(feature = "net")]
+const SOCK_TYPE_MASK: usize = 0xf;
+/// socket()/socketpair()/accept4() flag: make the descriptor O_NONBLOCK.
+#[cfg(feature = "net")]
+const SOCK_NONBLOCK: usize = 0x800;
+/// socket()/socketpair()/accept4() flag: set close-on-exec.
+#[cfg(feature = "net")]
+const SOCK_CLOEXEC: usize = 0x80000;
+/// send/recv flag: do not block for this call only.
+#[cfg(feature = "net")]
+const MSG_DONTWAIT: usize = 0x40;
+
+#[cfg(feature = "net")]
+use crate::net::socket::{SocketHandle, SocketNode};
 
 /// Convert user-space socket type to UnixSocketType.
+#[cfg(feature = "net")]
 fn to_unix_socket_type(
     sock_type: usize,
 ) -> Result<crate::net::unix_socket::UnixSocketType, SyscallError> {
-    match sock_type {
+    match sock_type & SOCK_TYPE_MASK {
         SOCK_STREAM => Ok(crate::net::unix_socket::UnixSocketType::Stream),
         SOCK_DGRAM => Ok(crate::net::unix_socket::UnixSocketType::Datagram),
         _ => Err(SyscallError::InvalidArgument),
     }
 }
 
+/// Credentials recorded on a new Unix socket (reported by SO_PEERCRED).
+#[cfg(feature = "net")]
+fn unix_credentials() -> crate::net::unix_socket::Credentials {
+    crate::process::current_process()
+        .map(|p| crate::net::unix_socket::Credentials {
+            pid: p.pid.0,
+            uid: p.euid(),
+            gid: p.egid(),
+        })
+        .unwrap_or_default()
+}
+
+/// Install a socket in the caller's file table, honouring the
+/// SOCK_NONBLOCK and SOCK_CLOEXEC bits of `flags`. If that fails, the
+/// node is dropped and the socket closed with it.
+#[cfg(feature = "net")]
+fn install_socket(node: SocketNode, flags: usize) -> SyscallResult {
+    let node: alloc::sync::Arc<dyn crate::fs::VfsNode> = alloc::sync::Arc::new(node);
+    let file = crate::fs::file::File::new(node, crate::fs::OpenFlags::read_write());
+    file.nonblock.store(
+        flags & SOCK_NONBLOCK != 0,
+        core::sync::atomic::Ordering::Relaxed,
+    );
+    let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = proc.file_table.lock();
+    file_table
+        .open_with_flags(alloc::sync::Arc::new(file), flags & SOCK_CLOEXEC != 0)
+        .map_err(|_| SyscallError::ResourceLimitExceeded)
+}
+
+/// The socket behind a file descriptor, along with its open file.
+#[cfg(feature = "net")]
+fn socket_file(
+    fd: usize,
+) -> Result<(SocketHandle, alloc::sync::Arc<crate::fs::file::File>), SyscallError> {
+    let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = proc.file_table.lock();
+    let file = file_table.get(fd).ok_or(SyscallError::BadFileDescriptor)?;
+    let handle = file
+        .node
+        .as_any()
+        .and_then(|any| any.downcast_ref::<SocketNode>())
+        .map(SocketNode::handle)
+        .ok_or(SyscallError::NotASocket)?;
+    Ok((handle, file))
+}
+
+/// The socket behind a file descriptor.
+#[cfg(feature = "net")]
+fn socket_handle(fd: usize) -> Result<SocketHandle, SyscallError> {
+    socket_file(fd).map(|(handle, _)| handle)
+}
+
+/// The SOL_SOCKET options of a socket.
+#[cfg(feature = "net")]
+fn socket_options(handle: SocketHandle) -> Result<crate::net::socket::SocketOptions, SyscallError> {
+    match handle {
+        SocketHandle::Inet(id) => crate::net::socket::getsockopt(id),
+        SocketHandle::Unix(id) => crate::net::unix_socket::options(id),
+    }
+    .map_err(|_| SyscallError::BadFileDescriptor)
+}
+
+/// Which timeout bounds a blocking socket call.
+#[derive(Clone, Copy)]
+#[cfg(feature = "net")]
+enum SocketWait {
+    /// SO_RCVTIMEO (recv, accept)
+    Recv,
+    /// SO_SNDTIMEO (send, connect)
+    Send,
+}
+
+/// Run a socket operation on `fd`, retrying while it would block.
+///
+/// Returns EAGAIN at once if the descriptor is O_NONBLOCK or `flags` has
+/// MSG_DONTWAIT, and after SO_RCVTIMEO/SO_SNDTIMEO if one is set. A
+/// pending signal interrupts the wait with EINTR. Waiting yields the CPU
+/// between attempts, like poll().
+#[cfg(feature = "net")]
+fn socket_io<T>(
+    fd: usize,
+    flags: usize,
+    wait: SocketWait,
+    mut op: impl FnMut(SocketHandle) -> Result<T, SyscallError>,
+) -> Result<T, SyscallError> {
+    // Holding the open file keeps the socket alive should another thread
+    // close the descriptor meanwhile.
+    let (handle, file) = socket_file(fd)?;
+    let nonblock =
+        flags & MSG_DONTWAIT != 0 || file.nonblock.load(core::sync::atomic::Ordering::Relaxed);
+    let options = socket_options(handle)?;
+    let timeout_ms = match wait {
+        SocketWait::Recv => options.recv_timeout_ms,
+        SocketWait::Send => options.send_timeout_ms,
+    };
+    let start = crate::timer::get_uptime_ms();
+
+    loop {
+        match op(handle) {
+            Err(SyscallError::WouldBlock) if !nonblock => {}
+            result => return result,
+        }
+        if timeout_ms.is_some_and(|t| crate::timer::get_uptime_ms() - start >= t) {
+            return Err(SyscallError::WouldBlock);
+        }
+        let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+        if proc.get_next_pending_signal().is_some() {
+            return Err(SyscallError::Interrupted);
+        }
+        crate::sched::yield_cpu();
+    }
+}
+
+/// Read a `(ip_u32, port_u16)` INET address from user space.
+#[cfg(feature = "net")]
+fn read_inet_addr(addr_ptr: usize) -> Result<crate::net::SocketAddr, SyscallError> {
+    let mut raw = [0u8; 6];
+    copy_bytes_from_user(addr_ptr, &mut raw)?;
+    let port = u16::from_ne_bytes([raw[4], raw[5]]).to_be();
+    Ok(crate::net::SocketAddr::v4(
+        crate::net::Ipv4Address([raw[0], raw[1], raw[2], raw[3]]),
+        port,
+    ))
+}
+
+/// Require the caller's sandbox profile, if it has one, to grant `need`.
+#[cfg(feature = "net")]
+fn require_network(need: crate::security::sandbox::Network) -> Result<(), SyscallError> {
+    match crate::process::current_process() {
+        Some(process) => crate::security::sandbox::check_network(process, need)
+            .map_err(|_| SyscallError::AccessDenied),
+        None => Ok(()),
+    }
+}
+
+/// Sandbox check for binding or connecting to a Unix socket path; names in
+/// the abstract namespace (leading NUL) are not files.
+#[cfg(feature = "net")]
+fn require_unix_path(path: &str) -> Result<(), SyscallError> {
+    if path.starts_with('\0') {
+        return Ok(());
+    }
+    filesystem::require_sandbox(
+        filesystem::AT_FDCWD,
+        path,
+        crate::security::AccessType::Write,
+        true,
+    )
+}
+
 /// SYS_SOCKET_CREATE: Create a new socket.
 ///
 /// # Arguments
 /// - domain: AF_UNIX (1) or AF_INET (2)
-/// - sock_type: SOCK_STREAM (1) or SOCK_DGRAM (2)
+/// - sock_type: SOCK_STREAM (1) or SOCK_DGRAM (2), optionally or'ed with
+///   SOCK_NONBLOCK and SOCK_CLOEXEC
+///
+/// Returns a file descriptor.
+#[cfg(feature = "net")]
 fn sys_socket_create(domain: usize, sock_type: usize) -> SyscallResult {
-    let pid = crate::process::current_process()
-        .map(|p| p.pid.0)
-        .unwrap_or(0);
-
-    match domain {
+    let handle = match domain {
         AF_UNIX => {
             let utype = to_unix_socket_type(sock_type)?;
-            crate::net::unix_socket::socket_create(utype, pid)
-                .map(|id| id as usize)
-                .map_err(|_| SyscallError::OutOfMemory)
+            let id = crate::net::unix_socket::socket_create(utype, unix_credentials())
+                .map_err(map_kernel_error)?;
+            SocketHandle::Unix(id)
         }
         AF_INET => {
+            require_network(crate::security::sandbox::Network::Client)?;
             let sock_domain = crate::net::socket::SocketDomain::Inet;
-            let (sock_tp, proto) = match sock_type {
+            let (sock_tp, proto) = match sock_type & SOCK_TYPE_MASK {
                 SOCK_STREAM => (
                     crate::net::socket::SocketType::Stream,
                     crate::net::socket::SocketProtocol::Tcp,
@@ -2813,210 +2689,228 @@ fn sys_socket_create(domain: usize, sock_type: usize) -> SyscallResult {
                 ),
                 _ => return Err(SyscallError::InvalidArgument),
             };
-            crate::net::socket::create_socket(sock_domain, sock_tp, proto)
-                .map(|id| id | INET_SOCKET_FLAG)
-                .map_err(|_| SyscallError::OutOfMemory)
+            let id = crate::net::socket::create_socket(sock_domain, sock_tp, proto)
+                .map_err(|_| SyscallError::OutOfMemory)?;
+            SocketHandle::Inet(id)
         }
-        _ => Err(SyscallError::InvalidArgument),
-    }
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+    install_socket(SocketNode::new(handle), sock_type)
 }
 
 /// SYS_SOCKET_BIND: Bind a socket to an address/path.
 ///
 /// # Arguments
-/// - socket_id: socket descriptor
-/// - addr_ptr: user-space pointer to address (path for AF_UNIX)
+/// - fd: socket descriptor
+/// - addr_ptr: user-space pointer to address (`sockaddr_un` for AF_UNIX)
 /// - addr_len: address length
-fn sys_socket_bind(socket_id: usize, addr_ptr: usize, _addr_len: usize) -> SyscallResult {
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        // Parse addr as (ip_u32, port_u16) from user space
-        validate_user_buffer(addr_ptr, 6)?;
-        // SAFETY: addr_ptr validated above as non-null, in user-space, 6 bytes.
-        let ip_bytes = unsafe { core::ptr::read_unaligned(addr_ptr as *const [u8; 4]) };
-        let port = unsafe { core::ptr::read_unaligned((addr_ptr + 4) as *const u16) }.to_be();
-        let addr = crate::net::SocketAddr::v4(crate::net::Ipv4Address(ip_bytes), port);
-        crate::net::socket::with_socket_mut(id, |s| s.bind(addr))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|_| SyscallError::InvalidState)?;
-        return Ok(0);
+#[cfg(feature = "net")]
+fn sys_socket_bind(fd: usize, addr_ptr: usize, addr_len: usize) -> SyscallResult {
+    match socket_handle(fd)? {
+        SocketHandle::Inet(id) => {
+            let addr = read_inet_addr(addr_ptr)?;
+            if addr.port() != 0 {
+                require_network(crate::security::sandbox::Network::Server)?;
+            }
+            crate::net::socket::with_socket_mut(id, |s| s.bind(addr))
+                .map_err(map_kernel_error)?
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        SocketHandle::Unix(id) => {
+            let path = network_ext_syscalls::parse_sockaddr_un(addr_ptr, addr_len)?;
+            require_unix_path(&path)?;
+            crate::net::unix_socket::socket_bind(id, &path)
+                .map(|()| 0)
+                .map_err(map_kernel_error)
+        }
     }
-    let path = read_user_name(addr_ptr, crate::net::unix_socket::UNIX_PATH_MAX)?;
-    crate::net::unix_socket::socket_bind(socket_id as u64, &path)
-        .map(|()| 0)
-        .map_err(|_| SyscallError::InvalidState)
 }
 
 /// SYS_SOCKET_LISTEN: Start listening on a bound socket.
-fn sys_socket_listen(socket_id: usize, backlog: usize) -> SyscallResult {
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        crate::net::socket::with_socket_mut(id, |s| s.listen(backlog))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|_| SyscallError::InvalidState)?;
-        return Ok(0);
+#[cfg(feature = "net")]
+fn sys_socket_listen(fd: usize, backlog: usize) -> SyscallResult {
+    match socket_handle(fd)? {
+        SocketHandle::Inet(id) => {
+            require_network(crate::security::sandbox::Network::Server)?;
+            crate::net::socket::with_socket_mut(id, |s| s.listen(backlog))
+                .map_err(map_kernel_error)?
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        SocketHandle::Unix(id) => crate::net::unix_socket::socket_listen(id, backlog)
+            .map(|()| 0)
+            .map_err(map_kernel_error),
     }
-    crate::net::unix_socket::socket_listen(socket_id as u64, backlog)
-        .map(|()| 0)
-        .map_err(|_| SyscallError::InvalidState)
 }
 
 /// SYS_SOCKET_CONNECT: Connect to a listening socket.
-fn sys_socket_connect(socket_id: usize, addr_ptr: usize, _addr_len: usize) -> SyscallResult {
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        validate_user_buffer(addr_ptr, 6)?;
-        // SAFETY: addr_ptr validated above as non-null, in user-space, 6 bytes.
-        let ip_bytes = unsafe { core::ptr::read_unaligned(addr_ptr as *const [u8; 4]) };
-        let port = unsafe { core::ptr::read_unaligned((addr_ptr + 4) as *const u16) }.to_be();
-        let addr = crate::net::SocketAddr::v4(crate::net::Ipv4Address(ip_bytes), port);
-        crate::net::socket::with_socket_mut(id, |s| s.connect(addr))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|_| SyscallError::InvalidState)?;
-        return Ok(0);
+///
+/// A Unix stream connect waits while the listener's backlog is full.
+#[cfg(feature = "net")]
+fn sys_socket_connect(fd: usize, addr_ptr: usize, addr_len: usize) -> SyscallResult {
+    let handle = socket_handle(fd)?;
+    match handle {
+        SocketHandle::Inet(id) => {
+            let addr = read_inet_addr(addr_ptr)?;
+            crate::net::socket::with_socket_mut(id, |s| s.connect(addr))
+                .map_err(map_kernel_error)?
+                .map_err(map_kernel_error)?;
+            Ok(0)
+        }
+        SocketHandle::Unix(_) => {
+            let path = network_ext_syscalls::parse_sockaddr_un(addr_ptr, addr_len)?;
+            require_unix_path(&path)?;
+            socket_io(fd, 0, SocketWait::Send, |handle| match handle {
+                SocketHandle::Unix(id) => crate::net::unix_socket::socket_connect(id, &path)
+                    .map(|()| 0)
+                    .map_err(map_kernel_error),
+                SocketHandle::Inet(_) => Err(SyscallError::InvalidState),
+            })
+        }
     }
-    let path = read_user_name(addr_ptr, crate::net::unix_socket::UNIX_PATH_MAX)?;
-    crate::net::unix_socket::socket_connect(socket_id as u64, &path)
-        .map(|()| 0)
-        .map_err(|_| SyscallError::InvalidState)
 }
 
 /// SYS_SOCKET_ACCEPT: Accept a pending connection.
 ///
 /// Linux ABI: `accept4(fd, addr, addrlen_ptr, flags)`.
 /// `addr_ptr` and `addrlen_ptr` are optional (may be 0). When non-null, the
-/// peer address is written back in sockaddr_in format.
+/// peer address is written back (sockaddr_in, or sockaddr_un for AF_UNIX).
+/// `flags` takes SOCK_NONBLOCK and SOCK_CLOEXEC for the new descriptor.
 ///
-/// Returns the new connected socket ID.
-fn sys_socket_accept(socket_id: usize, addr_ptr: usize, addrlen_ptr: usize) -> SyscallResult {
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        let result = crate::net::socket::with_socket(id, |s| s.accept())
-            .map_err(|_| SyscallError::InvalidState)?;
-        match result {
-            Ok((new_sock, remote)) => {
-                // Register the accepted socket in the socket table
-                let new_id = crate::net::socket::create_socket(
-                    new_sock.domain,
-                    new_sock.socket_type,
-                    new_sock.protocol,
-                )
-                .map_err(|_| SyscallError::OutOfMemory)?;
-
-                // Write peer address back if requested
-                if addr_ptr != 0 && addrlen_ptr != 0 {
-                    let _ = network_ext_syscalls::write_sockaddr(addr_ptr, &remote);
-                    // Write actual addrlen (16 for sockaddr_in)
-                    if validate_user_buffer(addrlen_ptr, 4).is_ok() {
-                        // SAFETY: addrlen_ptr validated above.
-                        unsafe {
-                            *(addrlen_ptr as *mut u32) = 16;
-                        }
-                    }
-                }
-
-                Ok(new_id | INET_SOCKET_FLAG)
+/// Waits for a connection unless the listening socket is non-blocking.
+/// Returns the file descriptor of the new connected socket.
+#[cfg(feature = "net")]
+fn sys_socket_accept(
+    fd: usize,
+    addr_ptr: usize,
+    addrlen_ptr: usize,
+    flags: usize,
+) -> SyscallResult {
+    let (node, remote) = socket_io(fd, 0, SocketWait::Recv, |handle| match handle {
+        SocketHandle::Inet(id) => {
+            let (new_sock, remote) = crate::net::socket::with_socket(id, |s| s.accept())
+                .map_err(map_kernel_error)?
+                .map_err(map_kernel_error)?;
+            // Register the accepted socket in the socket table
+            let new_id = crate::net::socket::create_socket(
+                new_sock.domain,
+                new_sock.socket_type,
+                new_sock.protocol,
+            )
+            .map_err(|_| SyscallError::OutOfMemory)?;
+            Ok((SocketNode::new(SocketHandle::Inet(new_id)), Some(remote)))
+        }
+        SocketHandle::Unix(id) => {
+            let (new_id, _) =
+                crate::net::unix_socket::socket_accept(id).map_err(map_kernel_error)?;
+            Ok((SocketNode::new(SocketHandle::Unix(new_id)), None))
+        }
+    })?;
+
+    // Write peer address back if requested
+    if addr_ptr != 0 && addrlen_ptr != 0 {
+        match (node.handle(), remote) {
+            (SocketHandle::Inet(_), Some(remote)) => {
+                let _ = network_ext_syscalls::write_sockaddr(addr_ptr, &remote);
+                // Write actual addrlen (16 for sockaddr_in)
+                let _ = copy_to_user(addrlen_ptr, &16u32);
+            }
+            (SocketHandle::Unix(new_id), _) => {
+                let peer = crate::net::unix_socket::peer_path(new_id).unwrap_or(None);
+                network_ext_syscalls::write_sockaddr_un(addr_ptr, addrlen_ptr, peer.as_deref())?;
             }
-            Err(crate::error::KernelError::WouldBlock) => Err(SyscallError::WouldBlock),
-            Err(_) => Err(SyscallError::InvalidState),
+            _ => {}
         }
-    } else {
-        crate::net::unix_socket::socket_accept(socket_id as u64)
-            .map(|(new_id, _connecting_id)| new_id as usize)
-            .map_err(|e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            })
     }
+
+    install_socket(node, flags)
 }
 
 /// SYS_SOCKET_SEND: Send data on a connected socket.
-fn sys_socket_send(socket_id: usize, buf_ptr: usize, buf_len: usize) -> SyscallResult {
-    validate_user_buffer(buf_ptr, buf_len)?;
-    // SAFETY: buf_ptr validated above as non-null, in user-space, within size
-    // limits.
-    let data = unsafe { core::slice::from_raw_parts(buf_ptr as *const u8, buf_len) };
-
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        crate::net::socket::with_socket_mut(id, |s| s.send(data, 0))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            })
-    } else {
-        crate::net::unix_socket::socket_send(socket_id as u64, data, None)
-            .map_err(|_| SyscallError::InvalidState)
-    }
+///
+/// `flags` takes MSG_DONTWAIT. Waits for buffer space unless the socket
+/// is non-blocking.
+#[cfg(feature = "net")]
+fn sys_socket_send(fd: usize, buf_ptr: usize, buf_len: usize, flags: usize) -> SyscallResult {
+    let data = copy_slice_from_user::<u8>(buf_ptr, buf_len)?;
+
+    socket_io(fd, flags, SocketWait::Send, |handle| match handle {
+        SocketHandle::Inet(id) => crate::net::socket::with_socket_mut(id, |s| s.send(&data, 0))
+            .map_err(map_kernel_error)?
+            .map_err(map_kernel_error),
+        SocketHandle::Unix(id) => {
+            crate::net::unix_socket::socket_send(id, &data, None).map_err(map_kernel_error)
+        }
+    })
 }
 
 /// SYS_SOCKET_RECV: Receive data from a socket.
-fn sys_socket_recv(socket_id: usize, buf_ptr: usize, buf_len: usize) -> SyscallResult {
-    validate_user_buffer(buf_ptr, buf_len)?;
-    // SAFETY: buf_ptr validated above as non-null, in user-space, within size
-    // limits.
-    let buf = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, buf_len) };
-
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        crate::net::socket::with_socket_mut(id, |s| s.recv(buf, 0))
-            .map_err(|_| SyscallError::InvalidState)?
-            .map_err(|e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            })
-    } else {
-        crate::net::unix_socket::socket_recv(socket_id as u64, buf)
+///
+/// `flags` takes MSG_DONTWAIT. Waits for data unless the socket is
+/// non-blocking.
+#[cfg(feature = "net")]
+fn sys_socket_recv(fd: usize, buf_ptr: usize, buf_len: usize, flags: usize) -> SyscallResult {
+    access_ok(buf_ptr, buf_len, Access::Write)?;
+    // Receive into a kernel buffer; only what arrived is copied out
+    let mut buf = alloc::vec![0u8; buf_len.min(65536)];
+
+    let received = socket_io(fd, flags, SocketWait::Recv, |handle| match handle {
+        SocketHandle::Inet(id) => crate::net::socket::with_socket_mut(id, |s| s.recv(&mut buf, 0))
+            .map_err(map_kernel_error)?
+            .map_err(map_kernel_error),
+        // Descriptors that arrive here have nowhere to go and are dropped
+        SocketHandle::Unix(id) => crate::net::unix_socket::socket_recv(id, &mut buf)
             .map(|(received, _rights)| received)
-            .map_err(|e| match e {
-                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
-                _ => SyscallError::InvalidState,
-            })
-    }
+            .map_err(map_kernel_error),
+    })?;
+    copy_bytes_to_user(buf_ptr, &buf[..received])?;
+    Ok(received)
 }
 
-/// SYS_SOCKET_CLOSE: Close a socket.
-fn sys_socket_close(socket_id: usize) -> SyscallResult {
-    if is_inet_socket(socket_id) {
-        let id = inet_socket_id(socket_id);
-        crate::net::socket::close_socket(id)
-            .map(|()| 0)
-            .map_err(|_| SyscallError::InvalidState)
-    } else {
-        crate::net::unix_socket::socket_close(socket_id as u64)
-            .map(|()| 0)
-            .map_err(|_| SyscallError::InvalidState)
-    }
+/// SYS_SOCKET_CLOSE: Close a socket descriptor, like `close()`. The socket
+/// itself goes away with the last descriptor that refers to it.
+#[cfg(feature = "net")]
+fn sys_socket_close(fd: usize) -> SyscallResult {
+    socket_handle(fd)?;
+    let proc = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let file_table = proc.file_table.lock();
+    file_table
+        .close(fd)
+        .map(|()| 0)
+        .map_err(|_| SyscallError::BadFileDescriptor)
 }
 
 /// SYS_SOCKET_PAIR: Create a connected socket pair.
 ///
 /// # Arguments
 /// - domain: AF_UNIX only
-/// - result_ptr: user-space pointer to write two u64 socket IDs
-fn sys_socket_pair(domain: usize, _sock_type: usize, result_ptr: usize) -> SyscallResult {
+/// - sock_type: SOCK_STREAM or SOCK_DGRAM, optionally or'ed with SOCK_NONBLOCK
+///   and SOCK_CLOEXEC
+/// - result_ptr: user-space pointer to write two i32 file descriptors
+#[cfg(feature = "net")]
+fn sys_socket_pair(domain: usize, sock_type: usize, result_ptr: usize) -> SyscallResult {
     if domain != AF_UNIX {
         return Err(SyscallError::InvalidArgument);
     }
+    let utype = to_unix_socket_type(sock_type)?;
     // Linux writes int sv[2] (two i32 values = 8 bytes).
-    validate_user_buffer(result_ptr, 2 * core::mem::size_of::<i32>())?;
-
-    let pid = crate::process::current_process()
-        .map(|p| p.pid.0)
-        .unwrap_or(0);
+    access_ok(result_ptr, 2 * core::mem::size_of::<i32>(), Access::Write)?;
 
     let (id_a, id_b) =
-        crate::net::unix_socket::socketpair(crate::net::unix_socket::UnixSocketType::Stream, pid)
-            .map_err(|_| SyscallError::OutOfMemory)?;
+        crate::net::unix_socket::socketpair(utype, unix_credentials()).map_err(map_kernel_error)?;
+    let node_b = SocketNode::new(SocketHandle::Unix(id_b));
+    let fd_a = install_socket(SocketNode::new(SocketHandle::Unix(id_a)), sock_type)?;
+    let fd_b = match install_socket(node_b, sock_type) {
+        Ok(fd) => fd,
+        Err(e) => {
+            let _ = sys_socket_close(fd_a);
+            return Err(e);
+        }
+    };
 
-    // SAFETY: result_ptr validated above as non-null and in user-space.
     // Write as i32 to match Linux ABI (int sv[2]).
-    unsafe {
-        let ptr = result_ptr as *mut i32;
-        *ptr = id_a as i32;
-        *ptr.add(1) = id_b as i32;
-    }
+    copy_to_user(result_ptr, &[fd_a as i32, fd_b as i32])?;
     Ok(0)
 }
 
@@ -3078,6 +2972,110 @@ mod tests {
         assert_eq!(Syscall::try_from(30).unwrap(), Syscall::CapabilityGrant);
     }
 
+    #[test]
+    fn test_syscall_try_from_capability_inspect() {
+        assert_eq!(Syscall::try_from(31).unwrap(), Syscall::CapabilityRevoke);
+        assert_eq!(Syscall::try_from(32).unwrap(), Syscall::CapabilityInspect);
+        assert_eq!(Syscall::try_from(33).unwrap(), Syscall::AuditRead);
+    }
+
+    #[test]
+    fn test_syscall_try_from_shm() {
+        assert_eq!(Syscall::try_from(210).unwrap(), Syscall::ShmOpen);
+        assert_eq!(Syscall::try_from(213).unwrap(), Syscall::ShmClose);
+    }
+
+    #[test]
+    fn test_syscall_try_from_pkg_verify() {
+        assert_eq!(Syscall::try_from(94).unwrap(), Syscall::PkgUpdate);
+        assert_eq!(Syscall::try_from(95).unwrap(), Syscall::PkgVerify);
+    }
+
+    #[test]
+    fn test_syscall_try_from_dmesg() {
+        assert_eq!(Syscall::try_from(80).unwrap(), Syscall::KernelGetInfo);
+        assert_eq!(Syscall::try_from(81).unwrap(), Syscall::Dmesg);
+    }
+
+    #[test]
+    fn test_syscall_try_from_trace_ctl() {
+        assert_eq!(Syscall::try_from(82).unwrap(), Syscall::TraceCtl);
+    }
+
+    #[test]
+    fn test_syscall_try_from_screenshot() {
+        assert_eq!(Syscall::try_from(234).unwrap(), Syscall::FbSwap);
+        assert_eq!(Syscall::try_from(235).unwrap(), Syscall::Screenshot);
+        assert_eq!(Syscall::try_from(236).unwrap(), Syscall::Notify);
+        assert_eq!(Syscall::try_from(237).unwrap(), Syscall::Window);
+        assert_eq!(Syscall::try_from(238).unwrap(), Syscall::InputInject);
+        assert_eq!(Syscall::try_from(239).unwrap(), Syscall::FbFlip);
+    }
+
+    #[test]
+    fn test_syscall_try_from_crash_dump() {
+        assert_eq!(Syscall::try_from(83).unwrap(), Syscall::CrashDump);
+    }
+
+    #[test]
+    fn test_syscall_try_from_watchdog() {
+        assert_eq!(Syscall::try_from(84).unwrap(), Syscall::Watchdog);
+    }
+
+    #[test]
+    fn test_syscall_try_from_reboot() {
+        assert_eq!(Syscall::try_from(85).unwrap(), Syscall::Reboot);
+        assert!(Syscall::try_from(87).is_err());
+    }
+
+    #[test]
+    fn test_syscall_try_from_net_firewall() {
+        assert_eq!(Syscall::try_from(256).unwrap(), Syscall::NetFirewall);
+        assert_eq!(Syscall::try_from(257).unwrap(), Syscall::NetPacketRing);
+        assert!(Syscall::try_from(258).is_err());
+    }
+
+    #[test]
+    fn test_syscall_try_from_boot_slot() {
+        assert_eq!(Syscall::try_from(96).unwrap(), Syscall::BootSlotInstall);
+        assert_eq!(Syscall::try_from(97).unwrap(), Syscall::BootSlotCommit);
+        assert_eq!(Syscall::try_from(98).unwrap(), Syscall::BootSlotQuery);
+        assert!(Syscall::try_from(99).is_err());
+    }
+
+    #[test]
+    fn test_syscall_try_from_credentials() {
+        assert_eq!(Syscall::try_from(181).unwrap(), Syscall::Getgroups);
+        assert_eq!(Syscall::try_from(182).unwrap(), Syscall::Setgroups);
+        assert_eq!(Syscall::try_from(355).unwrap(), Syscall::Setreuid);
+        assert_eq!(Syscall::try_from(358).unwrap(), Syscall::Getresgid);
+    }
+
+    #[test]
+    fn test_syscall_try_from_crypt() {
+        assert_eq!(Syscall::try_from(359).unwrap(), Syscall::Crypt);
+    }
+
+    #[test]
+    fn test_syscall_try_from_itimer() {
+        assert_eq!(Syscall::try_from(360).unwrap(), Syscall::Setitimer);
+        assert_eq!(Syscall::try_from(361).unwrap(), Syscall::Getitimer);
+        assert_eq!(Syscall::try_from(362).unwrap(), Syscall::Getrusage);
+        assert_eq!(Syscall::try_from(381).unwrap(), Syscall::Times);
+        assert_eq!(Syscall::try_from(382).unwrap(), Syscall::HeapProfile);
+        assert_eq!(Syscall::try_from(383).unwrap(), Syscall::SyscallFilter);
+        assert!(Syscall::try_from(387).is_err());
+    }
+
+    #[test]
+    fn test_syscall_try_from_crypto() {
+        assert_eq!(Syscall::try_from(363).unwrap(), Syscall::CryptoHash);
+        assert_eq!(Syscall::try_from(366).unwrap(), Syscall::CryptoAeadOpen);
+        assert_eq!(Syscall::try_from(368).unwrap(), Syscall::CryptoVerify);
+        assert_eq!(Syscall::try_from(369).unwrap(), Syscall::CryptoSign);
+        assert_eq!(Syscall::try_from(370).unwrap(), Syscall::Sendfile);
+    }
+
     #[test]
     fn test_syscall_try_from_thread_create() {
         assert_eq!(Syscall::try_from(40).unwrap(), Syscall::ThreadCreate);
@@ -3103,6 +3101,11 @@ mod tests {
         assert_eq!(Syscall::try_from(80).unwrap(), Syscall::KernelGetInfo);
     }
 
+    #[test]
+    fn test_syscall_try_from_abi_version() {
+        assert_eq!(Syscall::try_from(86).unwrap(), Syscall::AbiVersion);
+    }
+
     #[test]
     fn test_syscall_try_from_invalid() {
         assert!(Syscall::try_from(999).is_err());
@@ -3110,9 +3113,8 @@ mod tests {
 
     #[test]
     fn test_syscall_try_from_gap_value() {
-        // Values between defined syscalls should fail (e.g., 8 is between IPC and
+        // Values between defined syscalls should fail (e.g., 9 is between IPC and
         // Process)
-        assert!(Syscall::try_from(8).is_err());
         assert!(Syscall::try_from(9).is_err());
         assert!(Syscall::try_from(19).is_err());
         assert!(Syscall::try_from(25).is_err());
@@ -3131,6 +3133,10 @@ mod tests {
             (5, Syscall::IpcBindEndpoint),
             (6, Syscall::IpcShareMemory),
             (7, Syscall::IpcMapMemory),
+            (8, Syscall::IpcLookupEndpoint),
+            (384, Syscall::IpcNamespaceCreate),
+            (385, Syscall::IpcNamespaceAssign),
+            (386, Syscall::IpcNamespaceExport),
         ];
 
         for (num, expected) in &ipc_syscalls {
@@ -3249,13 +3255,16 @@ mod tests {
 
     #[test]
     fn test_syscall_error_values() {
-        assert_eq!(SyscallError::InvalidSyscall as i32, -1);
-        assert_eq!(SyscallError::InvalidArgument as i32, -2);
-        assert_eq!(SyscallError::PermissionDenied as i32, -3);
-        assert_eq!(SyscallError::ResourceNotFound as i32, -4);
-        assert_eq!(SyscallError::OutOfMemory as i32, -5);
-        assert_eq!(SyscallError::WouldBlock as i32, -6);
-        assert_eq!(SyscallError::InvalidCapability as i32, -10);
+        assert_eq!(SyscallError::InvalidSyscall.errno().to_ret(), -1);
+        assert_eq!(SyscallError::InvalidArgument.errno().to_ret(), -2);
+        assert_eq!(SyscallError::PermissionDenied.errno().to_ret(), -3);
+        assert_eq!(SyscallError::ResourceNotFound.errno().to_ret(), -4);
+        assert_eq!(SyscallError::OutOfMemory.errno().to_ret(), -5);
+        assert_eq!(SyscallError::WouldBlock.errno().to_ret(), -6);
+        assert_eq!(SyscallError::InvalidCapability.errno().to_ret(), -10);
+        assert_eq!(SyscallError::NotImplemented.errno(), Errno::ENOSYS);
+        assert_eq!(SyscallError::SymlinkLoop.errno(), Errno::ELOOP);
+        assert_eq!(SyscallError::NoData.errno(), Errno::ENODATA);
     }
 
     #[test]
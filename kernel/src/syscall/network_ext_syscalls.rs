@@ -1,9 +1,21 @@
 //! Network extension syscall handlers (Phase 6).
 //!
 //! Syscalls 250-255: sendto, recvfrom, getsockname, getpeername,
-//! setsockopt, getsockopt.
+//! setsockopt, getsockopt, for both AF_INET and AF_UNIX sockets; the
+//! socket ioctls that `ioctl` hands over: `FIONBIO`, and the interface
+//! configuration requests (`SIOCGIFCONF`, `SIOC[GS]IF*`) on AF_INET
+//! sockets; syscall 256, packet filter control; and syscall 257,
+//! kernel-bypass packet rings.
 
-use super::{SyscallError, SyscallResult};
+use alloc::{string::String, vec::Vec};
+
+use super::{
+    uaccess::{
+        access_ok, copy_bytes_to_user, copy_from_user, copy_slice_from_user, copy_slice_to_user,
+        copy_to_user, Access,
+    },
+    SocketHandle, SocketWait, SyscallError, SyscallResult,
+};
 
 /// Send data to a specific address (UDP-style).
 ///
@@ -14,30 +26,45 @@ use super::{SyscallError, SyscallResult};
 /// - `fd`: Socket file descriptor.
 /// - `buf_ptr`: User-space data buffer.
 /// - `buf_len`: Data length.
-/// - `_flags`: Send flags (MSG_DONTWAIT, etc.) -- currently ignored.
+/// - `flags`: Send flags; MSG_DONTWAIT is honoured.
 /// - `addr_ptr`: User-space sockaddr pointer (arg5 in Linux ABI).
 pub(super) fn sys_net_sendto(
     fd: usize,
     buf_ptr: usize,
     buf_len: usize,
-    _flags: usize,
+    flags: usize,
     addr_ptr: usize,
 ) -> SyscallResult {
-    super::validate_user_buffer(buf_ptr, buf_len)?;
     // addr_len (Linux arg6) is not available due to 5-arg handler limit.
     // Infer from sa_family: AF_INET=16, AF_INET6=28, AF_UNIX=110. Default 128.
     let addr_len = if addr_ptr != 0 {
-        infer_sockaddr_len(addr_ptr)
+        infer_sockaddr_len(addr_ptr)?
     } else {
         0
     };
-    if addr_ptr != 0 && addr_len > 0 {
-        super::validate_user_buffer(addr_ptr, addr_len)?;
-    }
 
-    // SAFETY: buf_ptr validated by validate_user_buffer above as non-null and
-    // within user-space.
-    let data = unsafe { core::slice::from_raw_parts(buf_ptr as *const u8, buf_len) };
+    let data = copy_slice_from_user::<u8>(buf_ptr, buf_len)?;
+    let data = &data[..];
+
+    if let SocketHandle::Unix(_) = super::socket_handle(fd)? {
+        let path = if addr_ptr != 0 {
+            let path = parse_sockaddr_un(addr_ptr, addr_len)?;
+            super::require_unix_path(&path)?;
+            Some(path)
+        } else {
+            None
+        };
+        return super::socket_io(fd, flags, SocketWait::Send, |handle| {
+            let SocketHandle::Unix(id) = handle else {
+                return Err(SyscallError::InvalidState);
+            };
+            match path {
+                Some(ref path) => crate::net::unix_socket::socket_sendto(id, data, path, None),
+                None => crate::net::unix_socket::socket_send(id, data, None),
+            }
+            .map_err(super::map_kernel_error)
+        });
+    }
 
     let dest = if addr_ptr != 0 {
         Some(parse_sockaddr(addr_ptr, addr_len)?)
@@ -45,7 +72,15 @@ pub(super) fn sys_net_sendto(
         None
     };
 
-    crate::net::socket::sendto(fd, data, dest.as_ref()).map_err(|_| SyscallError::IoError)
+    super::socket_io(fd, flags, SocketWait::Send, |handle| match handle {
+        SocketHandle::Inet(id) => {
+            crate::net::socket::sendto(id, data, dest.as_ref()).map_err(|e| match e {
+                crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
+                _ => SyscallError::IoError,
+            })
+        }
+        SocketHandle::Unix(_) => Err(SyscallError::InvalidState),
+    })
 }
 
 /// Receive data with sender address.
@@ -57,22 +92,41 @@ pub(super) fn sys_net_sendto(
 /// - `fd`: Socket file descriptor.
 /// - `buf_ptr`: User-space receive buffer.
 /// - `buf_len`: Buffer capacity.
-/// - `_flags`: Receive flags (MSG_DONTWAIT, etc.) -- currently ignored.
-/// - `addr_ptr`: User-space sockaddr buffer (may be 0).
+/// - `flags`: Receive flags; MSG_DONTWAIT is honoured.
+/// - `addr_ptr`: User-space sockaddr buffer (may be 0). For AF_UNIX it must
+///   hold a full `sockaddr_un`.
 pub(super) fn sys_net_recvfrom(
     fd: usize,
     buf_ptr: usize,
     buf_len: usize,
-    _flags: usize,
+    flags: usize,
     addr_ptr: usize,
 ) -> SyscallResult {
-    super::validate_user_buffer(buf_ptr, buf_len)?;
+    // Received into a kernel buffer, then copied out
+    access_ok(buf_ptr, buf_len, Access::Write)?;
+    let mut buf = alloc::vec![0u8; buf_len];
 
-    // SAFETY: buf_ptr validated by validate_user_buffer above as non-null and
-    // within user-space.
-    let buf = unsafe { core::slice::from_raw_parts_mut(buf_ptr as *mut u8, buf_len) };
+    if let SocketHandle::Unix(_) = super::socket_handle(fd)? {
+        let received = super::socket_io(fd, flags, SocketWait::Recv, |handle| match handle {
+            SocketHandle::Unix(id) => crate::net::unix_socket::socket_recv_from(id, &mut buf)
+                .map_err(super::map_kernel_error),
+            SocketHandle::Inet(_) => Err(SyscallError::InvalidState),
+        })?;
+        copy_bytes_to_user(buf_ptr, &buf[..received.len])?;
+        if addr_ptr != 0 {
+            put_sockaddr_un(addr_ptr, SOCKADDR_UN_LEN, received.from.as_deref())?;
+        }
+        return Ok(received.len);
+    }
 
-    let (n, src_addr) = crate::net::socket::recvfrom(fd, buf).map_err(|_| SyscallError::IoError)?;
+    let (n, src_addr) = super::socket_io(fd, flags, SocketWait::Recv, |handle| match handle {
+        SocketHandle::Inet(id) => crate::net::socket::recvfrom(id, &mut buf).map_err(|e| match e {
+            crate::error::KernelError::WouldBlock => SyscallError::WouldBlock,
+            _ => SyscallError::IoError,
+        }),
+        SocketHandle::Unix(_) => Err(SyscallError::InvalidState),
+    })?;
+    copy_bytes_to_user(buf_ptr, &buf[..n])?;
 
     if let (true, Some(addr)) = (addr_ptr != 0, src_addr) {
         write_sockaddr(addr_ptr, &addr)?;
@@ -83,40 +137,79 @@ pub(super) fn sys_net_recvfrom(
 
 /// Get the local address of a socket.
 pub(super) fn sys_net_getsockname(fd: usize, addr_ptr: usize, len_ptr: usize) -> SyscallResult {
-    super::validate_user_buffer(addr_ptr, 16)?;
-    super::validate_user_buffer(len_ptr, core::mem::size_of::<u32>())?;
+    let id = match super::socket_handle(fd)? {
+        SocketHandle::Inet(id) => id,
+        SocketHandle::Unix(id) => {
+            let path = crate::net::unix_socket::local_path(id).map_err(super::map_kernel_error)?;
+            write_sockaddr_un(addr_ptr, len_ptr, path.as_deref())?;
+            return Ok(0);
+        }
+    };
+    access_ok(len_ptr, core::mem::size_of::<u32>(), Access::Write)?;
 
-    let addr = crate::net::socket::getsockname(fd).map_err(|_| SyscallError::BadFileDescriptor)?;
+    let addr = crate::net::socket::getsockname(id).map_err(|_| SyscallError::BadFileDescriptor)?;
     write_sockaddr(addr_ptr, &addr)?;
 
     // Write actual address length
-    // SAFETY: len_ptr validated by validate_user_buffer above as non-null and
-    // within user-space.
-    unsafe {
-        *(len_ptr as *mut u32) = 16;
-    }
+    copy_to_user(len_ptr, &16u32)?;
 
     Ok(0)
 }
 
 /// Get the remote address of a connected socket.
 pub(super) fn sys_net_getpeername(fd: usize, addr_ptr: usize, len_ptr: usize) -> SyscallResult {
-    super::validate_user_buffer(addr_ptr, 16)?;
-    super::validate_user_buffer(len_ptr, core::mem::size_of::<u32>())?;
+    let id = match super::socket_handle(fd)? {
+        SocketHandle::Inet(id) => id,
+        SocketHandle::Unix(id) => {
+            let path = crate::net::unix_socket::peer_path(id).map_err(super::map_kernel_error)?;
+            write_sockaddr_un(addr_ptr, len_ptr, path.as_deref())?;
+            return Ok(0);
+        }
+    };
+    access_ok(len_ptr, core::mem::size_of::<u32>(), Access::Write)?;
 
-    let addr = crate::net::socket::getpeername(fd).map_err(|_| SyscallError::BadFileDescriptor)?;
+    let addr = crate::net::socket::getpeername(id).map_err(|_| SyscallError::BadFileDescriptor)?;
     write_sockaddr(addr_ptr, &addr)?;
 
-    // SAFETY: len_ptr validated by validate_user_buffer above as non-null and
-    // within user-space.
-    unsafe {
-        *(len_ptr as *mut u32) = 16;
-    }
+    copy_to_user(len_ptr, &16u32)?;
 
     Ok(0)
 }
 
+/// Socket option constants (`<sys/socket.h>`, `<netinet/tcp.h>`)
+const SOL_SOCKET: usize = 1;
+const SO_REUSEADDR: usize = 2;
+const SO_TYPE: usize = 3;
+const SO_ERROR: usize = 4;
+const SO_BROADCAST: usize = 6;
+const SO_SNDBUF: usize = 7;
+const SO_RCVBUF: usize = 8;
+const SO_KEEPALIVE: usize = 9;
+const SO_REUSEPORT: usize = 15;
+const SO_PEERCRED: usize = 17;
+const SO_RCVTIMEO: usize = 20;
+const SO_SNDTIMEO: usize = 21;
+const IPPROTO_TCP: usize = 6;
+const TCP_NODELAY: usize = 1;
+
+/// Bounds for SO_SNDBUF/SO_RCVBUF.
+const SOCKET_BUFFER_MIN: usize = 2048;
+const SOCKET_BUFFER_MAX: usize = 4 * 1024 * 1024;
+
+/// `struct timeval`, the value of SO_RCVTIMEO/SO_SNDTIMEO.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Timeval {
+    sec: i64,
+    usec: i64,
+}
+
 /// Set a socket option.
+///
+/// Supported: SO_REUSEADDR, SO_REUSEPORT, SO_BROADCAST, SO_KEEPALIVE,
+/// SO_SNDBUF, SO_RCVBUF, SO_RCVTIMEO, SO_SNDTIMEO and TCP_NODELAY. A zero
+/// timeout means none. Other options are accepted and ignored so that
+/// ported software setting them (SO_LINGER, IP_TOS, ...) keeps working.
 pub(super) fn sys_net_setsockopt(
     fd: usize,
     level: usize,
@@ -124,38 +217,154 @@ pub(super) fn sys_net_setsockopt(
     optval_ptr: usize,
     optlen: usize,
 ) -> SyscallResult {
-    if optval_ptr != 0 && optlen > 0 {
-        super::validate_user_buffer(optval_ptr, optlen)?;
+    use crate::net::socket::SocketOption;
+
+    let handle = super::socket_handle(fd)?;
+    let int_value = || -> Result<i32, SyscallError> {
+        if optlen < core::mem::size_of::<i32>() {
+            return Err(SyscallError::InvalidArgument);
+        }
+        copy_from_user(optval_ptr)
+    };
+    let buffer_size =
+        || int_value().map(|v| (v.max(0) as usize).clamp(SOCKET_BUFFER_MIN, SOCKET_BUFFER_MAX));
+    let timeout = || -> Result<Option<u64>, SyscallError> {
+        if optlen < core::mem::size_of::<Timeval>() {
+            return Err(SyscallError::InvalidArgument);
+        }
+        let tv: Timeval = copy_from_user(optval_ptr)?;
+        if tv.sec < 0 || !(0..1_000_000).contains(&tv.usec) {
+            return Err(SyscallError::InvalidArgument);
+        }
+        let ms = (tv.sec as u64)
+            .saturating_mul(1000)
+            .saturating_add((tv.usec as u64).div_ceil(1000));
+        Ok((ms != 0).then_some(ms))
+    };
+
+    let option = match (level, optname) {
+        (SOL_SOCKET, SO_REUSEADDR) => SocketOption::ReuseAddr(int_value()? != 0),
+        (SOL_SOCKET, SO_REUSEPORT) => SocketOption::ReusePort(int_value()? != 0),
+        (SOL_SOCKET, SO_BROADCAST) => SocketOption::Broadcast(int_value()? != 0),
+        (SOL_SOCKET, SO_KEEPALIVE) => SocketOption::KeepAlive(int_value()? != 0),
+        (SOL_SOCKET, SO_SNDBUF) => SocketOption::SendBufferSize(buffer_size()?),
+        (SOL_SOCKET, SO_RCVBUF) => SocketOption::RecvBufferSize(buffer_size()?),
+        (SOL_SOCKET, SO_RCVTIMEO) => SocketOption::RecvTimeout(timeout()?),
+        (SOL_SOCKET, SO_SNDTIMEO) => SocketOption::SendTimeout(timeout()?),
+        (IPPROTO_TCP, TCP_NODELAY) => SocketOption::NoDelay(int_value()? != 0),
+        _ => return Ok(0),
+    };
+
+    match handle {
+        SocketHandle::Inet(id) => crate::net::socket::setsockopt(id, option),
+        SocketHandle::Unix(id) => crate::net::unix_socket::set_option(id, option),
     }
-    crate::net::socket::setsockopt(fd, level as i32, optname as i32, optval_ptr, optlen)
-        .map_err(|_| SyscallError::InvalidArgument)
+    .map(|()| 0)
+    .map_err(|_| SyscallError::InvalidArgument)
 }
 
 /// Get a socket option.
+///
+/// Linux ABI: `getsockopt(fd, level, optname, optval, optlen_ptr)`.
+/// Reports the options `sys_net_setsockopt` stores, SO_TYPE, SO_ERROR
+/// (always 0: errors are reported by the failing call) and, on Unix
+/// sockets, SO_PEERCRED (a 12-byte `struct ucred`). Other options read
+/// as 0. `*optlen_ptr` gives the room at `optval_ptr` and receives the
+/// size written.
 pub(super) fn sys_net_getsockopt(
     fd: usize,
     level: usize,
     optname: usize,
     optval_ptr: usize,
+    optlen_ptr: usize,
 ) -> SyscallResult {
-    if optval_ptr != 0 {
-        super::validate_user_buffer(optval_ptr, 4)?;
+    use crate::net::socket::SocketType;
+
+    let handle = super::socket_handle(fd)?;
+    if optval_ptr == 0 || optlen_ptr == 0 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let room: u32 = copy_from_user(optlen_ptr)?;
+    let options = super::socket_options(handle)?;
+
+    let put = |bytes: &[u8]| -> SyscallResult {
+        if (room as usize) < bytes.len() {
+            return Err(SyscallError::InvalidArgument);
+        }
+        copy_bytes_to_user(optval_ptr, bytes)?;
+        copy_to_user(optlen_ptr, &(bytes.len() as u32))?;
+        Ok(0)
+    };
+    let int = |v: i32| put(&v.to_ne_bytes());
+    let flag = |b: bool| int(i32::from(b));
+    let timeval = |ms: Option<u64>| {
+        let ms = ms.unwrap_or(0);
+        let tv = [(ms / 1000) as i64, ((ms % 1000) * 1000) as i64];
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&tv[0].to_ne_bytes());
+        bytes[8..].copy_from_slice(&tv[1].to_ne_bytes());
+        put(&bytes)
+    };
+
+    match (level, optname) {
+        (SOL_SOCKET, SO_TYPE) => {
+            let sock_type = match handle {
+                SocketHandle::Inet(id) => {
+                    match crate::net::socket::with_socket(id, |s| s.socket_type)
+                        .map_err(|_| SyscallError::BadFileDescriptor)?
+                    {
+                        SocketType::Stream => 1,
+                        SocketType::Dgram => 2,
+                        SocketType::Raw => 3,
+                    }
+                }
+                SocketHandle::Unix(id) => {
+                    match crate::net::unix_socket::socket_type(id)
+                        .map_err(super::map_kernel_error)?
+                    {
+                        crate::net::unix_socket::UnixSocketType::Stream => 1,
+                        crate::net::unix_socket::UnixSocketType::Datagram => 2,
+                    }
+                }
+            };
+            int(sock_type)
+        }
+        (SOL_SOCKET, SO_PEERCRED) => {
+            let SocketHandle::Unix(id) = handle else {
+                return Err(SyscallError::InvalidArgument);
+            };
+            let cred =
+                crate::net::unix_socket::peer_credentials(id).map_err(super::map_kernel_error)?;
+            let mut ucred = [0u8; 12];
+            ucred[..4].copy_from_slice(&(cred.pid as u32).to_ne_bytes());
+            ucred[4..8].copy_from_slice(&cred.uid.to_ne_bytes());
+            ucred[8..].copy_from_slice(&cred.gid.to_ne_bytes());
+            put(&ucred)
+        }
+        (SOL_SOCKET, SO_ERROR) => int(0),
+        (SOL_SOCKET, SO_REUSEADDR) => flag(options.reuse_addr),
+        (SOL_SOCKET, SO_REUSEPORT) => flag(options.reuse_port),
+        (SOL_SOCKET, SO_BROADCAST) => flag(options.broadcast),
+        (SOL_SOCKET, SO_KEEPALIVE) => flag(options.keepalive),
+        (SOL_SOCKET, SO_SNDBUF) => int(options.send_buffer_size as i32),
+        (SOL_SOCKET, SO_RCVBUF) => int(options.recv_buffer_size as i32),
+        (SOL_SOCKET, SO_RCVTIMEO) => timeval(options.recv_timeout_ms),
+        (SOL_SOCKET, SO_SNDTIMEO) => timeval(options.send_timeout_ms),
+        (IPPROTO_TCP, TCP_NODELAY) => flag(options.no_delay),
+        _ => int(0),
     }
-    crate::net::socket::getsockopt(fd, level as i32, optname as i32, optval_ptr)
-        .map_err(|_| SyscallError::InvalidArgument)
 }
 
 /// Infer sockaddr length from sa_family when the actual length is unavailable
 /// (e.g., sendto where arg6 is lost due to 5-arg handler limit).
-fn infer_sockaddr_len(addr_ptr: usize) -> usize {
-    // SAFETY: addr_ptr was checked non-null by the caller; read only 2 bytes.
-    let family = unsafe { *(addr_ptr as *const u16) };
-    match family {
+fn infer_sockaddr_len(addr_ptr: usize) -> Result<usize, SyscallError> {
+    let family: u16 = copy_from_user(addr_ptr)?;
+    Ok(match family {
         2 => 16,  // AF_INET: sizeof(sockaddr_in)
         10 => 28, // AF_INET6: sizeof(sockaddr_in6)
         1 => 110, // AF_UNIX: sizeof(sockaddr_un)
         _ => 128, // Conservative default
-    }
+    })
 }
 
 /// Parse a sockaddr_in from user space.
@@ -164,19 +373,14 @@ fn parse_sockaddr(
     _addr_len: usize,
 ) -> Result<crate::net::SocketAddr, SyscallError> {
     // struct sockaddr_in { u16 family, u16 port_be, u32 addr_be, u8 zero[8] }
-    // SAFETY: addr_ptr was validated by the caller via validate_user_buffer.
-    let family = unsafe { *(addr_ptr as *const u16) };
-    if family != 2 {
+    let raw: [u8; 8] = copy_from_user(addr_ptr)?;
+    if u16::from_ne_bytes([raw[0], raw[1]]) != 2 {
         // AF_INET = 2
         return Err(SyscallError::InvalidArgument);
     }
-    // SAFETY: addr_ptr + 2 is within the validated sockaddr buffer.
-    let port_be = unsafe { *((addr_ptr + 2) as *const u16) };
-    // SAFETY: addr_ptr + 4 is within the validated sockaddr buffer.
-    let addr_be = unsafe { *((addr_ptr + 4) as *const u32) };
-
-    let port = u16::from_be(port_be);
-    let addr_bytes = addr_be.to_be_bytes();
+    let port = u16::from_be_bytes([raw[2], raw[3]]);
+    // The address is in network byte order, i.e. already in octet order.
+    let addr_bytes = [raw[4], raw[5], raw[6], raw[7]];
 
     Ok(crate::net::SocketAddr {
         ip: crate::net::IpAddress::V4(crate::net::Ipv4Address(addr_bytes)),
@@ -195,15 +399,882 @@ pub(super) fn write_sockaddr(
     };
 
     // struct sockaddr_in: family(2) + port_be(2) + addr_be(4) + zero(8)
-    // SAFETY: addr_ptr was validated by the caller via
-    // validate_user_buffer(addr_ptr, 16).
-    unsafe {
-        *(addr_ptr as *mut u16) = 2; // AF_INET
-        *((addr_ptr + 2) as *mut u16) = addr.port.to_be();
-        *((addr_ptr + 4) as *mut u32) = u32::from_be_bytes(bytes);
-        // Zero padding
-        core::ptr::write_bytes((addr_ptr + 8) as *mut u8, 0, 8);
+    let mut raw = [0u8; 16];
+    raw[..2].copy_from_slice(&2u16.to_ne_bytes()); // AF_INET
+    raw[2..4].copy_from_slice(&addr.port.to_be_bytes());
+    raw[4..8].copy_from_slice(&bytes);
+
+    copy_bytes_to_user(addr_ptr, &raw)
+}
+
+/// `sizeof(struct sockaddr_un)`: family + 108-byte path
+const SOCKADDR_UN_LEN: usize = 2 + crate::net::unix_socket::UNIX_PATH_MAX;
+
+/// Parse a `sockaddr_un` into a Unix socket name. A path is cut at its
+/// first NUL; an abstract name (leading NUL) is taken byte for byte up to
+/// `addr_len`, leading NUL included.
+pub(super) fn parse_sockaddr_un(addr_ptr: usize, addr_len: usize) -> Result<String, SyscallError> {
+    if !(3..=SOCKADDR_UN_LEN).contains(&addr_len) {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let raw = copy_slice_from_user::<u8>(addr_ptr, addr_len)?;
+    if u16::from_ne_bytes([raw[0], raw[1]]) != 1 {
+        // AF_UNIX = 1
+        return Err(SyscallError::InvalidArgument);
+    }
+    let name = &raw[2..];
+    let name = if name[0] == 0 {
+        name
+    } else {
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        &name[..end]
+    };
+    core::str::from_utf8(name)
+        .map(String::from)
+        .map_err(|_| SyscallError::InvalidArgument)
+}
+
+/// Write the `sockaddr_un` for `name` (just the family for an unnamed
+/// socket), truncated to `capacity` bytes. Returns its full length.
+pub(super) fn put_sockaddr_un(
+    addr_ptr: usize,
+    capacity: usize,
+    name: Option<&str>,
+) -> Result<usize, SyscallError> {
+    let mut raw = Vec::with_capacity(SOCKADDR_UN_LEN);
+    raw.extend_from_slice(&1u16.to_ne_bytes()); // AF_UNIX
+    if let Some(name) = name {
+        raw.extend_from_slice(name.as_bytes());
+        if !name.starts_with('\0') {
+            raw.push(0);
+        }
     }
+    let len = raw.len().min(capacity);
+    copy_bytes_to_user(addr_ptr, &raw[..len])?;
+    Ok(raw.len())
+}
 
+/// getsockname/getpeername for Unix sockets: `*len_ptr` is the buffer
+/// size on entry and the address length on return.
+pub(super) fn write_sockaddr_un(
+    addr_ptr: usize,
+    len_ptr: usize,
+    name: Option<&str>,
+) -> Result<(), SyscallError> {
+    let capacity: u32 = copy_from_user(len_ptr)?;
+    let len = put_sockaddr_un(addr_ptr, capacity as usize, name)?;
+    copy_to_user(len_ptr, &(len as u32))?;
     Ok(())
 }
+
+// ============================================================================
+// Interface configuration ioctls (SIOC*)
+// ============================================================================
+
+/// Interface ioctl requests, as in Linux `<linux/sockios.h>`
+const SIOCGIFNAME: usize = 0x8910;
+const SIOCGIFCONF: usize = 0x8912;
+const SIOCGIFFLAGS: usize = 0x8913;
+const SIOCSIFFLAGS: usize = 0x8914;
+const SIOCGIFADDR: usize = 0x8915;
+const SIOCSIFADDR: usize = 0x8916;
+const SIOCGIFNETMASK: usize = 0x891B;
+const SIOCSIFNETMASK: usize = 0x891C;
+const SIOCGIFMTU: usize = 0x8921;
+const SIOCSIFMTU: usize = 0x8922;
+const SIOCGIFHWADDR: usize = 0x8927;
+const SIOCGIFINDEX: usize = 0x8933;
+
+/// `sa_family` of SIOCGIFHWADDR results
+const ARPHRD_ETHER: u16 = 1;
+const ARPHRD_LOOPBACK: u16 = 772;
+
+/// `struct ifreq`: a name and a 24-byte union (sockaddr, flags, mtu, index)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IfReq {
+    name: [u8; 16],
+    data: [u8; 24],
+}
+
+impl IfReq {
+    fn new(name: &str) -> Self {
+        let mut req = Self {
+            name: [0; 16],
+            data: [0; 24],
+        };
+        let len = name.len().min(15);
+        req.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+        req
+    }
+
+    fn name(&self) -> Result<&str, SyscallError> {
+        let len = self.name.iter().position(|&b| b == 0).unwrap_or(16);
+        core::str::from_utf8(&self.name[..len]).map_err(|_| SyscallError::InvalidArgument)
+    }
+
+    fn int(&self) -> i32 {
+        i32::from_ne_bytes([self.data[0], self.data[1], self.data[2], self.data[3]])
+    }
+
+    fn set_int(&mut self, value: i32) {
+        self.data[..4].copy_from_slice(&value.to_ne_bytes());
+    }
+
+    /// The address of an AF_INET `sockaddr_in`.
+    fn inet_addr(&self) -> Result<crate::net::Ipv4Address, SyscallError> {
+        if u16::from_ne_bytes([self.data[0], self.data[1]]) != 2 {
+            return Err(SyscallError::InvalidArgument);
+        }
+        Ok(crate::net::Ipv4Address([
+            self.data[4],
+            self.data[5],
+            self.data[6],
+            self.data[7],
+        ]))
+    }
+
+    fn set_inet_addr(&mut self, addr: crate::net::Ipv4Address) {
+        self.data = [0; 24];
+        self.data[..2].copy_from_slice(&2u16.to_ne_bytes()); // AF_INET
+        self.data[4..8].copy_from_slice(&addr.0);
+    }
+}
+
+/// `struct ifconf` (64-bit layout)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IfConf {
+    len: i32,
+    _pad: i32,
+    buf: usize,
+}
+
+/// `ioctl(FIONBIO, int *)`: set or clear O_NONBLOCK
+const FIONBIO: usize = 0x5421;
+
+/// ioctls on a socket: `FIONBIO`, and the interface configuration
+/// requests on an AF_INET socket, as used by `ifconfig`/`ip`. Returns
+/// `None` for other requests and descriptors.
+///
+/// Reading interface settings works for everyone; changing flags,
+/// addresses or the MTU needs root.
+pub(super) fn handle_socket_ioctl(fd: usize, cmd: usize, arg: usize) -> Option<SyscallResult> {
+    if cmd != FIONBIO && !(0x8910..=0x8933).contains(&cmd) {
+        return None;
+    }
+    let (handle, file) = super::socket_file(fd).ok()?;
+    match (cmd, handle) {
+        (FIONBIO, _) => Some(set_nonblocking(&file, arg)),
+        (_, SocketHandle::Inet(_)) => Some(netif_ioctl(cmd, arg)),
+        (_, SocketHandle::Unix(_)) => None,
+    }
+}
+
+fn set_nonblocking(file: &crate::fs::file::File, arg: usize) -> SyscallResult {
+    let on: i32 = copy_from_user(arg)?;
+    file.nonblock
+        .store(on != 0, core::sync::atomic::Ordering::Relaxed);
+    Ok(0)
+}
+
+fn netif_ioctl(cmd: usize, arg: usize) -> SyscallResult {
+    use crate::net::{device, ip};
+
+    if cmd == SIOCGIFCONF {
+        return get_ifconf(arg);
+    }
+
+    let mut req: IfReq = copy_from_user(arg)?;
+
+    if matches!(
+        cmd,
+        SIOCSIFFLAGS | SIOCSIFADDR | SIOCSIFNETMASK | SIOCSIFMTU
+    ) {
+        let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+        if caller.euid() != 0 {
+            return Err(SyscallError::PermissionDenied);
+        }
+    }
+
+    if cmd == SIOCGIFNAME {
+        let index = req.int();
+        let info =
+            device::interface_by_index(index as u32).ok_or(SyscallError::ResourceNotFound)?;
+        req = IfReq::new(&info.name);
+        req.set_int(index);
+        copy_to_user(arg, &req)?;
+        return Ok(0);
+    }
+
+    let name = String::from(req.name()?);
+    let info = device::interface(&name).ok_or(SyscallError::ResourceNotFound)?;
+    let (addr, netmask) = ip::interface_address(&name)
+        .unwrap_or((crate::net::Ipv4Address::ANY, crate::net::Ipv4Address::ANY));
+
+    match cmd {
+        SIOCGIFFLAGS => req.set_int(info.flags as i32),
+        SIOCGIFADDR => req.set_inet_addr(addr),
+        SIOCGIFNETMASK => req.set_inet_addr(netmask),
+        SIOCGIFMTU => req.set_int(info.mtu as i32),
+        SIOCGIFINDEX => req.set_int(info.index as i32),
+        SIOCGIFHWADDR => {
+            let family = if info.flags & device::IFF_LOOPBACK != 0 {
+                ARPHRD_LOOPBACK
+            } else {
+                ARPHRD_ETHER
+            };
+            req.data = [0; 24];
+            req.data[..2].copy_from_slice(&family.to_ne_bytes());
+            req.data[2..8].copy_from_slice(&info.mac.0);
+        }
+        SIOCSIFFLAGS => {
+            // ifr_flags is a short
+            let flags = u16::from_ne_bytes([req.data[0], req.data[1]]) as u32;
+            device::set_up(&name, flags & device::IFF_UP != 0)
+                .map_err(|_| SyscallError::InvalidState)?;
+            return Ok(0);
+        }
+        SIOCSIFADDR => {
+            ip::set_interface_address(&name, req.inet_addr()?, netmask);
+            return Ok(0);
+        }
+        SIOCSIFNETMASK => {
+            if addr == crate::net::Ipv4Address::ANY {
+                return Err(SyscallError::NoSuchAddress);
+            }
+            ip::set_interface_address(&name, addr, req.inet_addr()?);
+            return Ok(0);
+        }
+        SIOCSIFMTU => {
+            let mtu = usize::try_from(req.int()).map_err(|_| SyscallError::InvalidArgument)?;
+            device::set_mtu(&name, mtu).map_err(|_| SyscallError::InvalidArgument)?;
+            return Ok(0);
+        }
+        _ => return Err(SyscallError::NotATerminal),
+    }
+
+    copy_to_user(arg, &req)?;
+    Ok(0)
+}
+
+/// SIOCGIFCONF: one `ifreq` with the IPv4 address of each configured
+/// interface. With a null buffer only the needed length is returned.
+fn get_ifconf(arg: usize) -> SyscallResult {
+    let mut conf: IfConf = copy_from_user(arg)?;
+    let entry = core::mem::size_of::<IfReq>();
+    let addresses = crate::net::ip::interface_addresses();
+
+    let count = if conf.buf == 0 {
+        addresses.len()
+    } else {
+        let room = usize::try_from(conf.len).map_err(|_| SyscallError::InvalidArgument)? / entry;
+        let count = addresses.len().min(room);
+        for (i, address) in addresses.iter().take(count).enumerate() {
+            let mut req = IfReq::new(&address.interface);
+            req.set_inet_addr(address.addr);
+            copy_to_user(conf.buf + i * entry, &req)?;
+        }
+        count
+    };
+    conf.len = (count * entry) as i32;
+    copy_to_user(arg, &conf)?;
+    Ok(0)
+}
+
+// ============================================================================
+// Packet filter control (syscall 256)
+// ============================================================================
+
+/// Operation selected by the first `net_firewall` argument.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FirewallOp {
+    /// Append the `FwRule` at `arg1` to its chain; returns the rule ID.
+    Append = 0,
+    /// Insert the `FwRule` at `arg1` at position `arg2` of its chain.
+    Insert = 1,
+    /// Delete rule `arg1`.
+    Delete = 2,
+    /// Delete every rule of chain `arg1` (`FW_CHAIN_ALL` = all chains).
+    Flush = 3,
+    /// Set the policy of chain `arg1` to `arg2` (0 = accept, 1 = drop).
+    Policy = 4,
+    /// Copy the rules to `arg1` (`arg2` bytes); returns the count.
+    List = 5,
+    /// Copy the tracked connections to `arg1` (`arg2` bytes); returns the
+    /// count.
+    Conntrack = 6,
+    /// Copy an `FwStats` to `arg1` (`arg2` bytes).
+    Stats = 7,
+    /// Zero the packet and byte counters.
+    Zero = 8,
+}
+
+impl TryFrom<usize> for FirewallOp {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FirewallOp::Append),
+            1 => Ok(FirewallOp::Insert),
+            2 => Ok(FirewallOp::Delete),
+            3 => Ok(FirewallOp::Flush),
+            4 => Ok(FirewallOp::Policy),
+            5 => Ok(FirewallOp::List),
+            6 => Ok(FirewallOp::Conntrack),
+            7 => Ok(FirewallOp::Stats),
+            8 => Ok(FirewallOp::Zero),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Filter chains addressable from user space, by `FwRule::chain` number
+const FIREWALL_CHAINS: [&str; 3] = ["INPUT", "OUTPUT", "FORWARD"];
+const FW_CHAIN_ALL: usize = 255;
+
+const FW_ACTION_ACCEPT: u8 = 0;
+const FW_ACTION_DROP: u8 = 1;
+const FW_ACTION_REJECT: u8 = 2;
+const FW_ACTION_LOG: u8 = 3;
+
+const FW_NEGATE_SRC: u8 = 1;
+const FW_NEGATE_DST: u8 = 2;
+
+/// A filter rule as exchanged with user space (`struct fw_rule`).
+///
+/// Zero fields match anything: protocol 0, state 0, prefix length 0, port
+/// range 0-0 and empty interface names.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FwRule {
+    /// Rule ID, packet and byte counters (filled in by `List`)
+    id: u64,
+    packets: u64,
+    bytes: u64,
+    /// Index into [`FIREWALL_CHAINS`]
+    chain: u8,
+    /// `FW_ACTION_*`
+    action: u8,
+    /// IP protocol number (1, 6 or 17)
+    protocol: u8,
+    /// 1 = new, 2 = established, 3 = related, 4 = invalid
+    state: u8,
+    /// `FW_NEGATE_*`
+    flags: u8,
+    src_prefix: u8,
+    dst_prefix: u8,
+    _pad: u8,
+    src_addr: [u8; 4],
+    dst_addr: [u8; 4],
+    /// Inclusive port ranges (TCP and UDP rules only)
+    src_port: [u16; 2],
+    dst_port: [u16; 2],
+    /// NUL-terminated interface names
+    in_interface: [u8; 16],
+    out_interface: [u8; 16],
+}
+
+/// Packet filter counters (`struct fw_stats`).
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct FwStats {
+    packets: u64,
+    dropped: u64,
+    connections: u64,
+    max_connections: u64,
+    /// Policy of each chain in [`FIREWALL_CHAINS`] (0 = accept, 1 = drop)
+    policy: [u8; 3],
+    _pad: [u8; 5],
+}
+
+/// A tracked connection (`struct fw_conn`), in the direction of its first
+/// packet.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FwConn {
+    src_addr: [u8; 4],
+    dst_addr: [u8; 4],
+    src_port: u16,
+    dst_port: u16,
+    protocol: u8,
+    /// As `FwRule::state`, plus 5 = TIME_WAIT
+    state: u8,
+    _pad: [u8; 2],
+    packets: u64,
+    bytes: u64,
+    /// Seconds until the entry expires
+    expires: u64,
+}
+
+/// Control the packet filter (root only).
+///
+/// Rules live in the INPUT, OUTPUT and FORWARD chains of the filter table
+/// and are evaluated in order; the chain policy applies when none matches.
+pub(super) fn sys_net_firewall(op: usize, arg1: usize, arg2: usize) -> SyscallResult {
+    use crate::net::firewall::{chain, conntrack};
+
+    let op = FirewallOp::try_from(op).map_err(|_| SyscallError::InvalidArgument)?;
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+    let with_engine = |f: &mut dyn FnMut(&mut chain::FirewallEngine) -> SyscallResult| {
+        chain::with_engine(|engine| f(engine)).unwrap_or(Err(SyscallError::InvalidState))
+    };
+
+    match op {
+        FirewallOp::Append | FirewallOp::Insert => {
+            let spec: FwRule = copy_from_user(arg1)?;
+            let chain_name = firewall_chain(spec.chain as usize)?;
+            let rule = firewall_rule(&spec)?;
+            with_engine(&mut |engine| {
+                let id = engine.add_rule(rule.clone());
+                if op == FirewallOp::Append {
+                    engine.add_to_filter_chain(chain_name, id);
+                } else {
+                    engine.insert_into_filter_chain(chain_name, arg2, id);
+                }
+                Ok(id as usize)
+            })
+        }
+        FirewallOp::Delete => with_engine(&mut |engine| {
+            if engine.delete_rule(arg1 as u64) {
+                Ok(0)
+            } else {
+                Err(SyscallError::ResourceNotFound)
+            }
+        }),
+        FirewallOp::Flush => {
+            let chains: &[&str] = if arg1 == FW_CHAIN_ALL {
+                &FIREWALL_CHAINS
+            } else {
+                core::slice::from_ref(&FIREWALL_CHAINS[firewall_chain_index(arg1)?])
+            };
+            with_engine(&mut |engine| {
+                for name in chains {
+                    engine.flush_filter_chain(name);
+                }
+                Ok(0)
+            })
+        }
+        FirewallOp::Policy => {
+            let chain_name = firewall_chain(arg1)?;
+            let policy = match arg2 {
+                0 => chain::ChainPolicy::Accept,
+                1 => chain::ChainPolicy::Drop,
+                _ => return Err(SyscallError::InvalidArgument),
+            };
+            with_engine(&mut |engine| {
+                engine.set_filter_policy(chain_name, policy);
+                Ok(0)
+            })
+        }
+        FirewallOp::List => {
+            let mut rules = Vec::new();
+            with_engine(&mut |engine| {
+                for (index, name) in FIREWALL_CHAINS.iter().enumerate() {
+                    let Some(chain) = engine.filter.get_chain(name) else {
+                        continue;
+                    };
+                    for &id in &chain.rule_ids {
+                        if let Some(rule) = engine.rule_engine.get_rule(id) {
+                            rules.push(firewall_rule_info(index as u8, rule));
+                        }
+                    }
+                }
+                Ok(0)
+            })?;
+            copy_records(arg1, arg2, &rules)
+        }
+        FirewallOp::Conntrack => {
+            let now = crate::timer::get_uptime_ms() / 1000;
+            let connections: Vec<FwConn> = conntrack::with_conntrack(|table| {
+                table
+                    .entries()
+                    .map(|entry| firewall_conn(entry, now))
+                    .collect()
+            })
+            .ok_or(SyscallError::InvalidState)?;
+            copy_records(arg1, arg2, &connections)
+        }
+        FirewallOp::Stats => {
+            if arg2 < core::mem::size_of::<FwStats>() {
+                return Err(SyscallError::InvalidArgument);
+            }
+            let mut stats = FwStats::default();
+            with_engine(&mut |engine| {
+                stats.packets = engine.total_packets;
+                stats.dropped = engine.dropped_packets;
+                for (index, name) in FIREWALL_CHAINS.iter().enumerate() {
+                    if let Some(chain) = engine.filter.get_chain(name) {
+                        stats.policy[index] = (chain.policy == chain::ChainPolicy::Drop) as u8;
+                    }
+                }
+                Ok(0)
+            })?;
+            if let Some(ct) = conntrack::with_conntrack(|table| table.stats()) {
+                stats.connections = ct.active_entries;
+                stats.max_connections = ct.max_entries;
+            }
+            copy_to_user(arg1, &stats)?;
+            Ok(0)
+        }
+        FirewallOp::Zero => with_engine(&mut |engine| {
+            engine.reset_counters();
+            Ok(0)
+        }),
+    }
+}
+
+fn firewall_chain_index(chain: usize) -> Result<usize, SyscallError> {
+    if chain < FIREWALL_CHAINS.len() {
+        Ok(chain)
+    } else {
+        Err(SyscallError::InvalidArgument)
+    }
+}
+
+fn firewall_chain(chain: usize) -> Result<&'static str, SyscallError> {
+    firewall_chain_index(chain).map(|index| FIREWALL_CHAINS[index])
+}
+
+/// Copy as many `records` as fit in `len` bytes at `buf`; returns the
+/// number copied.
+fn copy_records<T: Copy>(buf: usize, len: usize, records: &[T]) -> SyscallResult {
+    let count = records.len().min(len / core::mem::size_of::<T>());
+    copy_slice_to_user(buf, &records[..count])?;
+    Ok(count)
+}
+
+fn conntrack_state(
+    state: u8,
+) -> Result<Option<crate::net::firewall::conntrack::ConntrackState>, SyscallError> {
+    use crate::net::firewall::conntrack::ConntrackState;
+
+    Ok(match state {
+        0 => None,
+        1 => Some(ConntrackState::New),
+        2 => Some(ConntrackState::Established),
+        3 => Some(ConntrackState::Related),
+        4 => Some(ConntrackState::Invalid),
+        _ => return Err(SyscallError::InvalidArgument),
+    })
+}
+
+fn conntrack_state_number(state: crate::net::firewall::conntrack::ConntrackState) -> u8 {
+    use crate::net::firewall::conntrack::ConntrackState;
+
+    match state {
+        ConntrackState::New => 1,
+        ConntrackState::Established => 2,
+        ConntrackState::Related => 3,
+        ConntrackState::Invalid => 4,
+        ConntrackState::TimeWait => 5,
+    }
+}
+
+/// A NUL-terminated interface name; empty means any interface.
+fn interface_name(raw: &[u8; 16]) -> Result<Option<String>, SyscallError> {
+    let len = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    if len == 0 {
+        return Ok(None);
+    }
+    core::str::from_utf8(&raw[..len])
+        .map(|name| Some(String::from(name)))
+        .map_err(|_| SyscallError::InvalidArgument)
+}
+
+fn put_interface_name(name: Option<&String>) -> [u8; 16] {
+    let mut raw = [0u8; 16];
+    if let Some(name) = name {
+        let len = name.len().min(raw.len() - 1);
+        raw[..len].copy_from_slice(&name.as_bytes()[..len]);
+    }
+    raw
+}
+
+/// Build a kernel rule from its user-space description.
+fn firewall_rule(spec: &FwRule) -> Result<crate::net::firewall::rules::FirewallRule, SyscallError> {
+    use crate::net::{
+        firewall::rules::{
+            CidrAddress, FirewallRule, MatchCriteria, PortRange, Protocol, RuleAction,
+        },
+        Ipv4Address,
+    };
+
+    let action = match spec.action {
+        FW_ACTION_ACCEPT => RuleAction::Accept,
+        FW_ACTION_DROP => RuleAction::Drop,
+        FW_ACTION_REJECT => RuleAction::Reject,
+        FW_ACTION_LOG => RuleAction::Log,
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+    let protocol = match spec.protocol {
+        0 => Protocol::Any,
+        1 => Protocol::Icmp,
+        6 => Protocol::Tcp,
+        17 => Protocol::Udp,
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+    if spec.src_prefix > 32 || spec.dst_prefix > 32 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let port_range = |range: [u16; 2]| -> Result<Option<PortRange>, SyscallError> {
+        if range == [0, 0] {
+            return Ok(None);
+        }
+        // Ports only exist for TCP and UDP.
+        if range[0] > range[1] || !matches!(protocol, Protocol::Tcp | Protocol::Udp) {
+            return Err(SyscallError::InvalidArgument);
+        }
+        Ok(Some(PortRange::range(range[0], range[1])))
+    };
+
+    let mut criteria = MatchCriteria::new().with_protocol(protocol);
+    if spec.src_prefix != 0 {
+        criteria = criteria.with_src_ip(CidrAddress::new(
+            Ipv4Address(spec.src_addr),
+            spec.src_prefix,
+        ));
+    }
+    if spec.dst_prefix != 0 {
+        criteria = criteria.with_dst_ip(CidrAddress::new(
+            Ipv4Address(spec.dst_addr),
+            spec.dst_prefix,
+        ));
+    }
+    criteria.negate_src = spec.flags & FW_NEGATE_SRC != 0;
+    criteria.negate_dst = spec.flags & FW_NEGATE_DST != 0;
+    criteria.src_port = port_range(spec.src_port)?;
+    criteria.dst_port = port_range(spec.dst_port)?;
+    criteria.conn_state = conntrack_state(spec.state)?;
+    criteria.in_interface = interface_name(&spec.in_interface)?;
+    criteria.out_interface = interface_name(&spec.out_interface)?;
+
+    Ok(FirewallRule::new(0, criteria, action))
+}
+
+/// Describe a rule of chain `chain` for user space.
+fn firewall_rule_info(chain: u8, rule: &crate::net::firewall::rules::FirewallRule) -> FwRule {
+    use crate::net::firewall::rules::{Protocol, RuleAction};
+
+    let criteria = &rule.criteria;
+    let action = match rule.action {
+        RuleAction::Accept => FW_ACTION_ACCEPT,
+        RuleAction::Drop => FW_ACTION_DROP,
+        RuleAction::Reject => FW_ACTION_REJECT,
+        RuleAction::Log => FW_ACTION_LOG,
+        // Not created through this interface
+        _ => u8::MAX,
+    };
+    let protocol = match criteria.protocol {
+        Protocol::Any => 0,
+        Protocol::Icmp => 1,
+        Protocol::Tcp => 6,
+        Protocol::Udp => 17,
+        Protocol::Icmpv6 => 58,
+    };
+    let ports = |range: Option<crate::net::firewall::rules::PortRange>| {
+        range.map_or([0, 0], |r| [r.start, r.end])
+    };
+    let mut flags = 0;
+    if criteria.negate_src {
+        flags |= FW_NEGATE_SRC;
+    }
+    if criteria.negate_dst {
+        flags |= FW_NEGATE_DST;
+    }
+
+    FwRule {
+        id: rule.id,
+        packets: rule.packets,
+        bytes: rule.bytes,
+        chain,
+        action,
+        protocol,
+        state: criteria.conn_state.map_or(0, conntrack_state_number),
+        flags,
+        src_prefix: criteria.src_ip.map_or(0, |c| c.prefix_len),
+        dst_prefix: criteria.dst_ip.map_or(0, |c| c.prefix_len),
+        _pad: 0,
+        src_addr: criteria.src_ip.map_or([0; 4], |c| c.address.0),
+        dst_addr: criteria.dst_ip.map_or([0; 4], |c| c.address.0),
+        src_port: ports(criteria.src_port),
+        dst_port: ports(criteria.dst_port),
+        in_interface: put_interface_name(criteria.in_interface.as_ref()),
+        out_interface: put_interface_name(criteria.out_interface.as_ref()),
+    }
+}
+
+fn firewall_conn(entry: &crate::net::firewall::conntrack::ConntrackEntry, now: u64) -> FwConn {
+    let key = &entry.key;
+    FwConn {
+        src_addr: key.src_ip.0,
+        dst_addr: key.dst_ip.0,
+        src_port: key.src_port,
+        dst_port: key.dst_port,
+        protocol: key.protocol,
+        state: conntrack_state_number(entry.state),
+        _pad: [0; 2],
+        packets: entry.packet_count,
+        bytes: entry.byte_count,
+        expires: (entry.last_seen + entry.timeout_ticks).saturating_sub(now),
+    }
+}
+
+// ============================================================================
+// Kernel-bypass packet rings (syscall 257)
+// ============================================================================
+
+/// Operation selected by the first `net_packet_ring` argument.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacketRingOp {
+    /// Attach rings as described by the `PrAttach` at `arg1`, map them and
+    /// fill in its `addr` and `size`; returns the ring ID.
+    Attach = 0,
+    /// Unmap and free ring `arg1`.
+    Detach = 1,
+    /// Transmit the frames queued on ring `arg1`; returns the count.
+    Kick = 2,
+    /// Wait until ring `arg1` has received frames, for at most `arg2`
+    /// milliseconds (0 = forever); returns the pending count.
+    Wait = 3,
+    /// Give process `arg1` a capability for interface `arg2` (root only).
+    Grant = 4,
+}
+
+impl TryFrom<usize> for PacketRingOp {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PacketRingOp::Attach),
+            1 => Ok(PacketRingOp::Detach),
+            2 => Ok(PacketRingOp::Kick),
+            3 => Ok(PacketRingOp::Wait),
+            4 => Ok(PacketRingOp::Grant),
+            _ => Err(()),
+        }
+    }
+}
+
+/// `struct pr_attach` in `<veridian/pktring.h>`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PrAttach {
+    ifindex: u32,
+    rx_slots: u32,
+    tx_slots: u32,
+    /// `PR_DIVERT`
+    flags: u32,
+    /// Only deliver frames of this EtherType (0 = all)
+    ethertype: u16,
+    _pad: [u16; 3],
+    /// Out: address of the ring set in the caller
+    addr: u64,
+    /// Out: size of the ring set in bytes
+    size: u64,
+}
+
+fn packet_ring_error(err: crate::error::KernelError) -> SyscallError {
+    use crate::error::KernelError;
+
+    match err {
+        KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+        KernelError::NotFound { .. } | KernelError::ProcessNotFound { .. } => {
+            SyscallError::ResourceNotFound
+        }
+        KernelError::PermissionDenied { .. } => SyscallError::PermissionDenied,
+        KernelError::OutOfMemory { .. } | KernelError::ResourceExhausted { .. } => {
+            SyscallError::OutOfMemory
+        }
+        e => super::map_kernel_error(e),
+    }
+}
+
+/// Exchange raw frames with a NIC through rings shared with the driver.
+///
+/// Attaching needs a capability for the interface, which root hands out
+/// with `Grant`; see `crate::net::packet_ring` for the ring layout.
+pub(super) fn sys_net_packet_ring(op: usize, arg1: usize, arg2: usize) -> SyscallResult {
+    use crate::net::packet_ring;
+
+    let op = PacketRingOp::try_from(op).map_err(|_| SyscallError::InvalidArgument)?;
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let pid = caller.pid;
+    let ring_id = || u32::try_from(arg1).map_err(|_| SyscallError::InvalidArgument);
+
+    match op {
+        PacketRingOp::Attach => {
+            let mut req: PrAttach = copy_from_user(arg1)?;
+            let id = packet_ring::attach(
+                pid,
+                req.ifindex,
+                req.rx_slots,
+                req.tx_slots,
+                req.flags,
+                req.ethertype,
+            )
+            .map_err(packet_ring_error)?;
+            let (phys, size) = packet_ring::with_ring(pid, id, |r| (r.phys_addr(), r.size()))
+                .map_err(packet_ring_error)?;
+            let mapped = crate::mm::vas::map_physical_region_user(phys, size).and_then(|addr| {
+                packet_ring::set_user_addr(pid, id, addr).map_err(packet_ring_error)?;
+                req.addr = addr as u64;
+                req.size = size as u64;
+                copy_to_user(arg1, &req)
+            });
+            if let Err(e) = mapped {
+                let _ = detach_packet_ring(caller, id);
+                return Err(e);
+            }
+            Ok(id as usize)
+        }
+        PacketRingOp::Detach => detach_packet_ring(caller, ring_id()?).map(|_| 0),
+        PacketRingOp::Kick => packet_ring::kick(pid, ring_id()?).map_err(packet_ring_error),
+        PacketRingOp::Wait => {
+            let id = ring_id()?;
+            let start = crate::timer::get_uptime_ms();
+            loop {
+                let pending = packet_ring::rx_pending(pid, id).map_err(packet_ring_error)?;
+                if pending > 0 {
+                    return Ok(pending as usize);
+                }
+                if arg2 != 0 && crate::timer::get_uptime_ms() - start >= arg2 as u64 {
+                    return Err(SyscallError::WouldBlock);
+                }
+                if caller.get_next_pending_signal().is_some() {
+                    return Err(SyscallError::Interrupted);
+                }
+                crate::sched::yield_cpu();
+            }
+        }
+        PacketRingOp::Grant => {
+            if caller.euid() != 0 {
+                return Err(SyscallError::PermissionDenied);
+            }
+            let target = crate::process::ProcessId(arg1 as u64);
+            let index = u32::try_from(arg2).map_err(|_| SyscallError::InvalidArgument)?;
+            packet_ring::grant(target, index).map_err(packet_ring_error)?;
+            Ok(0)
+        }
+    }
+}
+
+/// Unmap ring `id` from `caller` (if it got mapped) and free it.
+fn detach_packet_ring(caller: &crate::process::Process, id: u32) -> Result<(), SyscallError> {
+    use crate::net::packet_ring;
+
+    let addr =
+        packet_ring::with_ring(caller.pid, id, |r| r.user_addr()).map_err(packet_ring_error)?;
+    if addr != 0 {
+        let _ = caller
+            .memory_space
+            .lock()
+            .unmap_region(crate::mm::VirtualAddress(addr as u64));
+    }
+    packet_ring::detach(caller.pid, id).map_err(packet_ring_error)
+}
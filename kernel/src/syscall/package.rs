@@ -5,41 +5,20 @@
 //! `pkg::PackageManager` singleton after validating arguments and
 //! checking capabilities.
 
-use super::{validate_user_ptr_typed, validate_user_string_ptr, SyscallError, SyscallResult};
+use super::{
+    uaccess::{copy_slice_from_user, copy_to_user, strncpy_from_user},
+    SyscallError, SyscallResult,
+};
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 #[cfg(feature = "alloc")]
-use alloc::{string::String, vec::Vec};
+use alloc::string::String;
 
-/// Read a null-terminated string from a user-space pointer.
-///
-/// Returns the string contents up to `max_len` bytes.
+/// Read a null-terminated string of at most `max_len` bytes from a
+/// user-space pointer.
 fn read_user_string(ptr: usize, max_len: usize) -> Result<String, SyscallError> {
-    // Validate pointer is in user space
-    validate_user_string_ptr(ptr)?;
-
-    // SAFETY: ptr was validated as non-null and in user-space above. We read
-    // bytes one at a time from the user-space pointer until we find a null
-    // terminator or reach the max_len limit. The caller must provide a valid,
-    // null-terminated string in mapped user memory.
-    let bytes = unsafe {
-        let mut buf = Vec::new();
-        let mut p = ptr as *const u8;
-
-        for _ in 0..max_len {
-            let byte = *p;
-            if byte == 0 {
-                break;
-            }
-            buf.push(byte);
-            p = p.add(1);
-        }
-        buf
-    };
-
-    let s = core::str::from_utf8(&bytes).map_err(|_| SyscallError::InvalidArgument)?;
-    Ok(String::from(s))
+    strncpy_from_user(ptr, max_len)
 }
 
 /// Install a package by name (SYS_PKG_INSTALL = 90)
@@ -50,6 +29,7 @@ fn read_user_string(ptr: usize, max_len: usize) -> Result<String, SyscallError>
 ///
 /// # Returns
 /// 0 on success
+#[cfg(feature = "pkg")]
 pub fn sys_pkg_install(name_ptr: usize, _name_len: usize) -> SyscallResult {
     let name = read_user_string(name_ptr, 256)?;
 
@@ -92,6 +72,7 @@ pub fn sys_pkg_install(name_ptr: usize, _name_len: usize) -> SyscallResult {
 ///
 /// # Returns
 /// 0 on success
+#[cfg(feature = "pkg")]
 pub fn sys_pkg_remove(name_ptr: usize, _name_len: usize) -> SyscallResult {
     let name = read_user_string(name_ptr, 256)?;
 
@@ -133,6 +114,7 @@ pub fn sys_pkg_remove(name_ptr: usize, _name_len: usize) -> SyscallResult {
 ///
 /// # Returns
 /// 1 if package is installed, 0 if not found
+#[cfg(feature = "pkg")]
 pub fn sys_pkg_query(name_ptr: usize, _info_buf: usize) -> SyscallResult {
     let name = read_user_string(name_ptr, 256)?;
 
@@ -151,6 +133,7 @@ pub fn sys_pkg_query(name_ptr: usize, _info_buf: usize) -> SyscallResult {
 ///
 /// # Returns
 /// Number of installed packages
+#[cfg(feature = "pkg")]
 pub fn sys_pkg_list(buf_ptr: usize, _buf_size: usize) -> SyscallResult {
     let count = match crate::pkg::with_package_manager(|mgr| mgr.list_installed().len()) {
         Some(n) => n,
@@ -159,16 +142,7 @@ pub fn sys_pkg_list(buf_ptr: usize, _buf_size: usize) -> SyscallResult {
 
     // If a buffer was provided, write the count there
     if buf_ptr != 0 {
-        // Validate buffer pointer is in user space and aligned for usize
-        validate_user_ptr_typed::<usize>(buf_ptr)?;
-
-        // SAFETY: buf_ptr was validated as non-null, in user-space, properly
-        // sized and aligned for usize above. We write a single usize value
-        // (the package count).
-        unsafe {
-            let out = buf_ptr as *mut usize;
-            *out = count;
-        }
+        copy_to_user(buf_ptr, &count)?;
     }
 
     Ok(count)
@@ -181,6 +155,7 @@ pub fn sys_pkg_list(buf_ptr: usize, _buf_size: usize) -> SyscallResult {
 ///
 /// # Returns
 /// 0 on success
+#[cfg(feature = "pkg")]
 pub fn sys_pkg_update(_flags: usize) -> SyscallResult {
     // Check capabilities -- updating repos is a privileged operation
     let current = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -209,3 +184,167 @@ pub fn sys_pkg_update(_flags: usize) -> SyscallResult {
         None => Err(SyscallError::InvalidState),
     }
 }
+
+/// Largest archive `sys_pkg_verify` accepts (64 MiB).
+#[cfg(feature = "pkg")]
+const MAX_VPK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Verify a `.vpk` archive signature (SYS_PKG_VERIFY = 95)
+///
+/// Used by `pkgd` before it unpacks anything. The signature is checked
+/// against the package manager's trusted key ring plus the keys listed in
+/// `/etc/pkg/trusted.keys`, and the signer must meet the signature policy's
+/// minimum trust level. If the policy does not require signatures, an
+/// unverifiable archive is accepted at trust level 0.
+///
+/// # Arguments
+/// - `buf_ptr`: Pointer to the complete archive
+/// - `len`: Archive length in bytes
+///
+/// # Returns
+/// The signer's trust level (0 = untrusted .. 3 = core)
+#[cfg(feature = "pkg")]
+pub fn sys_pkg_verify(buf_ptr: usize, len: usize) -> SyscallResult {
+    use crate::pkg::{format::TrustLevel, vpk};
+
+    if len > MAX_VPK_SIZE {
+        return Err(SyscallError::InvalidArgument);
+    }
+    // The archive is copied in before parsing so the caller cannot change
+    // it between verification steps.
+    let data = copy_slice_from_user::<u8>(buf_ptr, len)?;
+    let archive = vpk::VpkArchive::parse(&data).map_err(|_| SyscallError::InvalidArgument)?;
+
+    let (mut keys, policy) = crate::pkg::with_package_manager(|mgr| {
+        (mgr.trusted_keys().clone(), mgr.signature_policy().clone())
+    })
+    .ok_or(SyscallError::InvalidState)?;
+    if let Ok(text) = crate::fs::read_file(vpk::TRUSTED_KEYS_PATH) {
+        vpk::load_trusted_keys(&mut keys, &String::from_utf8_lossy(&text));
+    }
+
+    match archive.verify(&keys) {
+        Ok(level) if level >= policy.minimum_trust_level => Ok(level as usize),
+        Ok(_) => Err(SyscallError::PermissionDenied),
+        Err(_) if !policy.require_signatures => {
+            crate::println!("[PKG] WARNING: accepting unverified .vpk (policy: not required)");
+            Ok(TrustLevel::Untrusted as usize)
+        }
+        Err(_) => Err(SyscallError::PermissionDenied),
+    }
+}
+
+/// Largest kernel image `sys_boot_slot_install` accepts (256 MiB).
+const MAX_KERNEL_IMAGE_SIZE: usize = 256 * 1024 * 1024;
+
+/// Map an A/B boot slot error to a system call error.
+fn boot_slot_error(err: crate::error::KernelError) -> SyscallError {
+    use crate::error::KernelError;
+
+    match err {
+        KernelError::NotFound { .. } | KernelError::NotInitialized { .. } => {
+            SyscallError::ResourceNotFound
+        }
+        KernelError::InvalidArgument { .. } => SyscallError::InvalidArgument,
+        KernelError::InvalidState { .. } => SyscallError::WouldBlock,
+        _ => SyscallError::IoError,
+    }
+}
+
+/// Only root may change the boot slots.
+fn require_root() -> Result<(), SyscallError> {
+    let caller = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+    Ok(())
+}
+
+/// Stage a kernel image in the inactive A/B boot slot
+/// (SYS_BOOT_SLOT_INSTALL = 96)
+///
+/// Used by `pkgd` when a package ships `/boot/veridian-kernel`. The slot is
+/// made active as a trial: it boots on the next reset and is rolled back
+/// unless user space commits it in time (see `bootslot`). Restricted
+/// to uid 0.
+///
+/// # Arguments
+/// - `buf_ptr`: Pointer to the kernel ELF image
+/// - `len`: Image length in bytes
+///
+/// # Returns
+/// The slot index (0 = A, 1 = B). Fails with `WouldBlock` while the active
+/// slot is still waiting to be committed.
+pub fn sys_boot_slot_install(buf_ptr: usize, len: usize) -> SyscallResult {
+    require_root()?;
+    if len == 0 || len > MAX_KERNEL_IMAGE_SIZE {
+        return Err(SyscallError::InvalidArgument);
+    }
+    // The image is copied in so the checksum matches what is written.
+    let image = copy_slice_from_user::<u8>(buf_ptr, len)?;
+
+    crate::bootslot::install(&image).map_err(boot_slot_error)
+}
+
+/// Commit the booted A/B boot slot (SYS_BOOT_SLOT_COMMIT = 97)
+///
+/// The boot-success handshake: init calls this once user space is up.
+/// Restricted to uid 0.
+///
+/// # Returns
+/// 1 if a trial slot was committed, 0 if it already was. Fails with
+/// `ResourceNotFound` when not booted from an A/B disk.
+pub fn sys_boot_slot_commit() -> SyscallResult {
+    require_root()?;
+    crate::bootslot::commit()
+        .map(usize::from)
+        .map_err(boot_slot_error)
+}
+
+/// One slot in [`BootSlotStatus`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BootSlotEntry {
+    /// 0 = empty, 1 = good, 2 = pending, 3 = bad
+    pub state: u32,
+    pub tries_left: u32,
+    pub image_len: u32,
+    pub generation: u32,
+}
+
+/// A/B boot slot status, as returned by `sys_boot_slot_query`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BootSlotStatus {
+    /// Index of the active slot.
+    pub active: u32,
+    pub reserved: u32,
+    pub slots: [BootSlotEntry; 2],
+}
+
+/// Report the A/B boot slot status (SYS_BOOT_SLOT_QUERY = 98)
+///
+/// # Arguments
+/// - `status_ptr`: Pointer to a `BootSlotStatus`
+///
+/// # Returns
+/// 0 on success; `ResourceNotFound` when not booted from an A/B disk.
+pub fn sys_boot_slot_query(status_ptr: usize) -> SyscallResult {
+    let record = crate::bootslot::record().ok_or(SyscallError::ResourceNotFound)?;
+
+    let mut status = BootSlotStatus {
+        active: record.active as u32,
+        ..Default::default()
+    };
+    for (out, slot) in status.slots.iter_mut().zip(record.slots.iter()) {
+        *out = BootSlotEntry {
+            state: slot.state as u32,
+            tries_left: slot.tries_left as u32,
+            image_len: slot.image_len,
+            generation: slot.generation,
+        };
+    }
+
+    copy_to_user(status_ptr, &status)?;
+    Ok(0)
+}
@@ -4,14 +4,23 @@
 //! creation, termination, and state management.
 
 use alloc::format;
-use core::slice;
 
-use super::{validate_user_buffer, validate_user_string_ptr, SyscallError, SyscallResult};
+use super::{
+    uaccess::{
+        access_ok, copy_bytes_to_user, copy_from_user, copy_slice_from_user, copy_slice_to_user,
+        copy_to_user, user_range, Access,
+    },
+    SyscallError, SyscallResult,
+};
 #[cfg(target_arch = "x86_64")]
 use crate::arch::context::ThreadContext;
 use crate::process::{
-    create_thread, current_process, exec_process, exit::exit_process, exit_thread, fork_process,
-    get_thread_tid, set_thread_affinity, ProcessId, ProcessPriority, ThreadId,
+    create_thread,
+    creds::{CredError, Credentials, NGROUPS_MAX},
+    current_process, exec_process,
+    exit::exit_process,
+    exit_thread, fork_process, get_thread_tid, set_thread_affinity, ProcessId, ProcessPriority,
+    ThreadId,
 };
 
 /// Fork the current process
@@ -39,6 +48,9 @@ pub fn sys_fork() -> SyscallResult {
                 }
             }
 
+            // Child resolves service names in the parent's IPC namespace
+            crate::ipc::namespace::inherit_namespace(current.pid, child_pid);
+
             // In parent process, return child PID
             Ok(child_pid.0 as usize)
         }
@@ -65,36 +77,36 @@ pub fn sys_fork() -> SyscallResult {
 pub fn sys_exec(path_ptr: usize, argv_ptr: usize, envp_ptr: usize) -> SyscallResult {
     use crate::syscall::userspace::{copy_string_array_from_user_tracked, copy_string_from_user};
 
-    // Validate path pointer is in user space
-    validate_user_string_ptr(path_ptr)?;
-
     // Copy path from user space
-    // SAFETY: path_ptr was validated as non-null and in user-space above.
-    // copy_string_from_user reads a null-terminated string from the user-space
-    // pointer with length bounds checking. The caller must provide valid mapped
-    // user memory.
-    let path = unsafe { copy_string_from_user(path_ptr)? };
+    let path = copy_string_from_user(path_ptr)?;
 
     crate::println!("[SYS_EXEC] path=\"{}\"", path);
 
+    // The caller needs execute permission on the image
+    if let Some(vfs_lock) = crate::fs::try_get_vfs() {
+        if let Ok(node) = vfs_lock.read().resolve_path(&path) {
+            let creds = current_process()
+                .map(|p| p.credentials())
+                .unwrap_or_default();
+            crate::fs::check_access(&*node, &creds, crate::process::creds::ACCESS_EXEC)
+                .map_err(super::map_kernel_error)?;
+        }
+    }
+
     // Parse argv and envp arrays from user space with cumulative ARG_MAX
     // enforcement. A single counter tracks total bytes across both argv and
     // envp (string data + NUL terminators + pointer slots). If the combined
     // size exceeds ARG_MAX (131072 bytes), returns E2BIG.
-    //
-    // SAFETY: argv_ptr and envp_ptr are user-space pointers to null-terminated
-    // arrays of string pointers. copy_string_array_from_user_tracked handles
-    // null pointer checks internally and bounds-checks string lengths.
     let mut arg_total_bytes: usize = 0;
-    let argv = unsafe { copy_string_array_from_user_tracked(argv_ptr, &mut arg_total_bytes)? };
+    let argv = copy_string_array_from_user_tracked(argv_ptr, &mut arg_total_bytes)?;
 
-    let mut envp = unsafe { copy_string_array_from_user_tracked(envp_ptr, &mut arg_total_bytes)? };
+    let mut envp = copy_string_array_from_user_tracked(envp_ptr, &mut arg_total_bytes)?;
 
-    // If envp is empty (NULL pointer from user-space), inherit the parent
-    // process's environment variables. This handles the case where libc's
-    // `environ` is NULL (e.g., GCC's libiberty overrides `environ` symbol)
-    // or the caller explicitly passes NULL for envp.
-    if envp.is_empty() {
+    // A NULL envp inherits the kernel's copy of the caller's environment.
+    // This handles the case where libc's `environ` is NULL (e.g., GCC's
+    // libiberty overrides `environ` symbol). An empty array is an empty
+    // environment.
+    if envp_ptr == 0 {
         if let Some(current) = current_process() {
             let parent_env = current.env_vars.lock();
             for (key, value) in parent_env.iter() {
@@ -138,9 +150,7 @@ pub fn sys_exec(path_ptr: usize, argv_ptr: usize, envp_ptr: usize) -> SyscallRes
             #[cfg(target_arch = "x86_64")]
             // SAFETY: current_thread() returns the thread that called exec.
             // Its context was updated by exec_process with the new entry
-            // point and stack pointer. swapgs undoes the swapgs from
-            // syscall_entry so GS_BASE/KERNEL_GS_BASE are correct for
-            // user mode. enter_usermode builds an iretq frame and
+            // point and stack pointer. enter_usermode builds an iretq frame and
             // transitions to Ring 3.
             unsafe {
                 let current_thread =
@@ -183,9 +193,10 @@ pub fn sys_exec(path_ptr: usize, argv_ptr: usize, envp_ptr: usize) -> SyscallRes
                     }
                 }
 
-                // Undo the swapgs from syscall_entry so GS_BASE and
-                // KERNEL_GS_BASE are correct for user mode.
-                core::arch::asm!("swapgs");
+                // Instead of undoing the swapgs from syscall_entry, point
+                // both GS bases at the per-CPU area again; the old image's
+                // GS, if it loaded one, does not survive exec.
+                crate::sched::percpu::init_cpu(crate::sched::percpu::cpu_id());
 
                 crate::arch::x86_64::usermode::enter_usermode(
                     entry, stack, 0x33, // User CS (Ring 3)
@@ -197,6 +208,7 @@ pub fn sys_exec(path_ptr: usize, argv_ptr: usize, envp_ptr: usize) -> SyscallRes
             #[cfg(not(target_arch = "x86_64"))]
             Err(SyscallError::InvalidState)
         }
+        Err(crate::error::KernelError::PermissionDenied { .. }) => Err(SyscallError::AccessDenied),
         Err(_e) => Err(SyscallError::ResourceNotFound),
     }
 }
@@ -285,7 +297,7 @@ pub fn sys_exit(exit_code: usize) -> SyscallResult {
 /// - status_ptr: Pointer to store exit status
 /// - options: Wait options bitmask (WNOHANG=1, WUNTRACED=2, WCONTINUED=8)
 pub fn sys_wait(pid: isize, status_ptr: usize, options: usize) -> SyscallResult {
-    use crate::{process::exit::WaitOptions, syscall::userspace::copy_to_user};
+    use crate::process::exit::WaitOptions;
 
     let wait_pid = if pid == -1 {
         None
@@ -312,11 +324,7 @@ pub fn sys_wait(pid: isize, status_ptr: usize, options: usize) -> SyscallResult
         Ok((child_pid, exit_status)) => {
             // Write exit status to user space if pointer provided
             if status_ptr != 0 {
-                // SAFETY: status_ptr is non-zero; copy_to_user validates it is within
-                // user-space.
-                unsafe {
-                    copy_to_user(status_ptr, &exit_status)?;
-                }
+                copy_to_user(status_ptr, &exit_status)?;
             }
             Ok(child_pid.0 as usize)
         }
@@ -362,12 +370,12 @@ pub fn sys_thread_create(
     // Validate entry point and stack pointer are in user space.
     // Entry point needs at least 1 byte (code); stack needs at least
     // pointer-sized space to hold a return address.
-    validate_user_buffer(entry_point, 1)?;
-    validate_user_buffer(stack_ptr, core::mem::size_of::<usize>())?;
+    user_range(entry_point, 1)?;
+    user_range(stack_ptr, core::mem::size_of::<usize>())?;
 
     // TLS pointer is optional (0 means none)
     if tls_ptr != 0 {
-        validate_user_buffer(tls_ptr, 1)?;
+        user_range(tls_ptr, 1)?;
     }
 
     match create_thread(entry_point, stack_ptr, arg, tls_ptr) {
@@ -397,8 +405,6 @@ pub fn sys_gettid() -> SyscallResult {
 /// - tid: Thread ID to join
 /// - retval_ptr: Pointer to store thread return value
 pub fn sys_thread_join(tid: usize, retval_ptr: usize) -> SyscallResult {
-    use crate::syscall::userspace::copy_to_user;
-
     let target_tid = ThreadId(tid as u64);
 
     // Get current process
@@ -432,12 +438,7 @@ pub fn sys_thread_join(tid: usize, retval_ptr: usize) -> SyscallResult {
                 // Return exit code to user
                 if retval_ptr != 0 {
                     let exit_value = exit_code as usize;
-                    // SAFETY: retval_ptr is a non-zero user-space pointer.
-                    // copy_to_user validates the pointer and copies the
-                    // usize exit value to the user buffer.
-                    unsafe {
-                        copy_to_user(retval_ptr, &exit_value)?;
-                    }
+                    copy_to_user(retval_ptr, &exit_value)?;
                 }
 
                 return Ok(0);
@@ -461,10 +462,11 @@ pub fn sys_thread_join(tid: usize, retval_ptr: usize) -> SyscallResult {
 /// - cpuset_ptr: Pointer to CPU set
 /// - cpuset_size: Size of CPU set
 pub fn sys_thread_setaffinity(tid: usize, cpuset_ptr: usize, cpuset_size: usize) -> SyscallResult {
-    if cpuset_size == 0 {
+    // Only the first 64 CPUs are supported; the rest of the set is ignored
+    if cpuset_size < 8 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(cpuset_ptr, cpuset_size)?;
+    access_ok(cpuset_ptr, cpuset_size, Access::Read)?;
 
     let target_tid = if tid == 0 {
         get_thread_tid()
@@ -472,23 +474,9 @@ pub fn sys_thread_setaffinity(tid: usize, cpuset_ptr: usize, cpuset_size: usize)
         ThreadId(tid as u64)
     };
 
-    // Read CPU set from user space
-    // SAFETY: cpuset_ptr was validated as non-zero and cpuset_size > 0
-    // above. The caller must provide a valid, readable user-space buffer
-    // of at least cpuset_size bytes containing the CPU affinity mask.
-    let cpuset = unsafe { slice::from_raw_parts(cpuset_ptr as *const u8, cpuset_size) };
-
-    // Extract CPU mask from cpuset (simplified)
-    let cpu_mask = if cpuset_size >= 8 {
-        // Slice is exactly 8 bytes (cpuset_size >= 8 checked above)
-        let bytes: [u8; 8] = match cpuset[0..8].try_into() {
-            Ok(b) => b,
-            Err(_) => return Err(SyscallError::InvalidArgument),
-        };
-        u64::from_le_bytes(bytes)
-    } else {
-        return Err(SyscallError::InvalidArgument);
-    };
+    // Read the CPU mask from user space
+    let bytes: [u8; 8] = copy_from_user(cpuset_ptr)?;
+    let cpu_mask = u64::from_le_bytes(bytes);
 
     match set_thread_affinity(target_tid, cpu_mask) {
         Ok(_) => Ok(0),
@@ -503,14 +491,12 @@ pub fn sys_thread_setaffinity(tid: usize, cpuset_ptr: usize, cpuset_size: usize)
 /// - cpuset_ptr: Pointer to store CPU set
 /// - cpuset_size: Size of CPU set buffer
 pub fn sys_thread_getaffinity(tid: usize, cpuset_ptr: usize, cpuset_size: usize) -> SyscallResult {
-    use crate::syscall::userspace::{copy_slice_to_user, validate_user_ptr};
-
     if cpuset_ptr == 0 || cpuset_size == 0 {
         return Err(SyscallError::InvalidArgument);
     }
 
     // Validate user pointer
-    validate_user_ptr(cpuset_ptr as *const u8, cpuset_size)?;
+    access_ok(cpuset_ptr, cpuset_size, Access::Write)?;
 
     let target_tid = if tid == 0 {
         get_thread_tid()
@@ -535,12 +521,7 @@ pub fn sys_thread_getaffinity(tid: usize, cpuset_ptr: usize, cpuset_size: usize)
     let mask_bytes = cpu_mask.to_le_bytes();
     let bytes_to_copy = cpuset_size.min(8);
 
-    // SAFETY: cpuset_ptr was validated as non-zero and checked via
-    // validate_user_ptr above. copy_slice_to_user copies the CPU mask
-    // bytes to the user-space buffer.
-    unsafe {
-        copy_slice_to_user(cpuset_ptr, &mask_bytes[..bytes_to_copy])?;
-    }
+    copy_bytes_to_user(cpuset_ptr, &mask_bytes[..bytes_to_copy])?;
 
     Ok(0)
 }
@@ -564,9 +545,9 @@ pub fn sys_setpriority(which: usize, who: usize, priority: usize) -> SyscallResu
     } else {
         let target_pid = ProcessId(who as u64);
 
-        // Modifying another process's priority requires MODIFY right
-        // on a Process capability for that process
-        if target_pid != current.pid {
+        // Modifying another process's priority requires root or the
+        // MODIFY right on a Process capability for that process
+        if target_pid != current.pid && current.euid() != 0 {
             let cap_space = current.capability_space.lock();
             let has_permission = {
                 let mut found = false;
@@ -638,91 +619,127 @@ pub fn sys_setpriority(which: usize, who: usize, priority: usize) -> SyscallResu
 }
 
 // ============================================================================
-// Identity syscalls (170-175)
+// Identity syscalls (170-175, 181-182, 355-358)
 // ============================================================================
 
+/// Apply `f` to the calling process's credentials.
+fn update_credentials(f: impl FnOnce(&mut Credentials) -> Result<(), CredError>) -> SyscallResult {
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    let mut creds = proc.creds.lock();
+    f(&mut creds)?;
+    Ok(0)
+}
+
+/// Copy a `(real, effective, saved)` triple out to three user `u32`s.
+fn write_id_triple(ptrs: [usize; 3], ids: [u32; 3]) -> SyscallResult {
+    for ptr in ptrs {
+        access_ok(ptr, core::mem::size_of::<u32>(), Access::Write)?;
+    }
+    for (ptr, id) in ptrs.into_iter().zip(ids) {
+        copy_to_user(ptr, &id)?;
+    }
+    Ok(0)
+}
+
 /// Get real user ID (SYS_GETUID = 170)
 pub fn sys_getuid() -> SyscallResult {
     let proc = current_process().ok_or(SyscallError::InvalidState)?;
-    Ok(proc.uid as usize)
+    Ok(proc.uid() as usize)
 }
 
 /// Get effective user ID (SYS_GETEUID = 171)
-///
-/// VeridianOS does not yet distinguish real/effective UIDs, so this returns
-/// the same value as getuid.
 pub fn sys_geteuid() -> SyscallResult {
-    sys_getuid()
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    Ok(proc.euid() as usize)
 }
 
 /// Get real group ID (SYS_GETGID = 172)
 pub fn sys_getgid() -> SyscallResult {
     let proc = current_process().ok_or(SyscallError::InvalidState)?;
-    Ok(proc.gid as usize)
+    Ok(proc.gid() as usize)
 }
 
 /// Get effective group ID (SYS_GETEGID = 173)
 pub fn sys_getegid() -> SyscallResult {
-    sys_getgid()
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    Ok(proc.egid() as usize)
 }
 
 /// Set user ID (SYS_SETUID = 174)
 ///
-/// Only uid 0 (root) can change to a different UID. Non-root processes
-/// may only "set" their uid to the current value (a no-op).
+/// With effective uid 0 this sets the real, effective and saved UIDs.
+/// Otherwise only the effective UID may be set, and only to the real or
+/// saved UID.
 pub fn sys_setuid(uid: usize) -> SyscallResult {
-    let proc = current_process().ok_or(SyscallError::InvalidState)?;
-    let current_uid = proc.uid;
-    let new_uid = uid as u32;
+    update_credentials(|c| c.set_uid(uid as u32))
+}
 
-    // Non-root can only set uid to current value (no-op)
-    if current_uid != 0 && new_uid != current_uid {
-        return Err(SyscallError::PermissionDenied);
-    }
+/// Set group ID (SYS_SETGID = 175)
+///
+/// Same privilege rules as `setuid`, applied to the group IDs.
+pub fn sys_setgid(gid: usize) -> SyscallResult {
+    update_credentials(|c| c.set_gid(gid as u32))
+}
 
-    // If already the requested uid, nothing to do
-    if new_uid == current_uid {
-        return Ok(0);
+/// Get supplementary groups (SYS_GETGROUPS = 181)
+///
+/// # Arguments
+/// - `size`: Capacity of `list` in entries; 0 queries the count only.
+/// - `list`: User buffer of `u32` group IDs.
+///
+/// # Returns
+/// Number of supplementary groups.
+pub fn sys_getgroups(size: usize, list: usize) -> SyscallResult {
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    let creds = proc.credentials();
+    let groups = creds.groups();
+    if size == 0 {
+        return Ok(groups.len());
+    }
+    if size < groups.len() {
+        return Err(SyscallError::InvalidArgument);
     }
+    copy_slice_to_user(list, groups)?;
+    Ok(groups.len())
+}
 
-    // Root changing uid: get mutable reference via process table
-    let pid = proc.pid;
-    if let Some(proc_mut) = crate::process::table::get_process_mut(pid) {
-        proc_mut.uid = new_uid;
-        Ok(0)
-    } else {
-        Err(SyscallError::InvalidState)
+/// Set supplementary groups (SYS_SETGROUPS = 182)
+///
+/// Requires effective uid 0. At most `NGROUPS_MAX` groups.
+pub fn sys_setgroups(size: usize, list: usize) -> SyscallResult {
+    if size > NGROUPS_MAX {
+        return Err(SyscallError::InvalidArgument);
     }
+    let groups = copy_slice_from_user::<u32>(list, size)?;
+    update_credentials(|c| c.set_groups(&groups))
 }
 
-/// Set group ID (SYS_SETGID = 175)
+/// Set real and effective user IDs (SYS_SETREUID = 355)
 ///
-/// Only uid 0 (root) can change to a different GID. Non-root processes
-/// may only "set" their gid to the current value (a no-op).
-pub fn sys_setgid(gid: usize) -> SyscallResult {
-    let proc = current_process().ok_or(SyscallError::InvalidState)?;
-    let current_uid = proc.uid;
-    let current_gid = proc.gid;
-    let new_gid = gid as u32;
+/// Pass -1 to leave an ID unchanged.
+pub fn sys_setreuid(ruid: usize, euid: usize) -> SyscallResult {
+    update_credentials(|c| c.set_reuid(ruid as u32, euid as u32))
+}
 
-    // Non-root can only set gid to current value (no-op)
-    if current_uid != 0 && new_gid != current_gid {
-        return Err(SyscallError::PermissionDenied);
-    }
+/// Set real and effective group IDs (SYS_SETREGID = 356)
+///
+/// Pass -1 to leave an ID unchanged.
+pub fn sys_setregid(rgid: usize, egid: usize) -> SyscallResult {
+    update_credentials(|c| c.set_regid(rgid as u32, egid as u32))
+}
 
-    // If already the requested gid, nothing to do
-    if new_gid == current_gid {
-        return Ok(0);
-    }
+/// Get real, effective and saved user IDs (SYS_GETRESUID = 357)
+pub fn sys_getresuid(ruid_ptr: usize, euid_ptr: usize, suid_ptr: usize) -> SyscallResult {
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    let c = proc.credentials();
+    write_id_triple([ruid_ptr, euid_ptr, suid_ptr], [c.ruid, c.euid, c.suid])
+}
 
-    // Root changing gid: get mutable reference via process table
-    let pid = proc.pid;
-    if let Some(proc_mut) = crate::process::table::get_process_mut(pid) {
-        proc_mut.gid = new_gid;
-        Ok(0)
-    } else {
-        Err(SyscallError::InvalidState)
-    }
+/// Get real, effective and saved group IDs (SYS_GETRESGID = 358)
+pub fn sys_getresgid(rgid_ptr: usize, egid_ptr: usize, sgid_ptr: usize) -> SyscallResult {
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    let c = proc.credentials();
+    write_id_triple([rgid_ptr, egid_ptr, sgid_ptr], [c.rgid, c.egid, c.sgid])
 }
 
 // ============================================================================
@@ -928,6 +945,71 @@ pub fn sys_tcgetpgrp(fd: usize) -> SyscallResult {
 static CONSOLE_FOREGROUND_PGID: core::sync::atomic::AtomicU64 =
     core::sync::atomic::AtomicU64::new(0);
 
+/// Foreground process group of the kernel console (0 = none).
+pub(super) fn console_foreground_pgid() -> u64 {
+    CONSOLE_FOREGROUND_PGID.load(core::sync::atomic::Ordering::Acquire)
+}
+
+/// Session that has the system console as its controlling terminal (0 = none).
+static CONSOLE_SESSION: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+
+/// Whether any live (non-zombie) process belongs to session `sid`.
+fn session_alive(sid: u64) -> bool {
+    let mut alive = false;
+    crate::process::table::PROCESS_TABLE.for_each(|p| {
+        if p.sid.load(core::sync::atomic::Ordering::Acquire) == sid
+            && !matches!(
+                p.get_state(),
+                crate::process::ProcessState::Zombie | crate::process::ProcessState::Dead
+            )
+        {
+            alive = true;
+        }
+    });
+    alive
+}
+
+/// Make the console the caller's controlling terminal (`TIOCSCTTY`).
+///
+/// The caller must be a session leader. If another session that still has
+/// live members owns the console, only root passing `steal` may take it.
+/// The caller's process group becomes the console foreground group.
+pub(super) fn console_acquire(steal: bool) -> SyscallResult {
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    let sid = proc.sid.load(core::sync::atomic::Ordering::Acquire);
+    if sid != proc.pid.0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let owner = CONSOLE_SESSION.load(core::sync::atomic::Ordering::Acquire);
+    if owner != 0 && owner != sid && session_alive(owner) && !(steal && proc.euid() == 0) {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    CONSOLE_SESSION.store(sid, core::sync::atomic::Ordering::Release);
+    CONSOLE_FOREGROUND_PGID.store(
+        proc.pgid.load(core::sync::atomic::Ordering::Acquire),
+        core::sync::atomic::Ordering::Release,
+    );
+    Ok(0)
+}
+
+/// Give up the console as controlling terminal (`TIOCNOTTY`).
+///
+/// Only has an effect when called by the leader of the owning session.
+pub(super) fn console_release() -> SyscallResult {
+    let proc = current_process().ok_or(SyscallError::InvalidState)?;
+    let sid = proc.sid.load(core::sync::atomic::Ordering::Acquire);
+    if CONSOLE_SESSION.load(core::sync::atomic::Ordering::Acquire) != sid {
+        return Err(SyscallError::NotATerminal);
+    }
+    if sid == proc.pid.0 {
+        CONSOLE_SESSION.store(0, core::sync::atomic::Ordering::Release);
+        CONSOLE_FOREGROUND_PGID.store(0, core::sync::atomic::Ordering::Release);
+    }
+    Ok(0)
+}
+
 /// Get process priority
 ///
 /// # Arguments
@@ -992,11 +1074,8 @@ pub fn sys_getenv(
     }
 
     // Read the variable name from user space
-    validate_user_buffer(name_ptr, name_len)?;
-    // SAFETY: name_ptr validated by validate_user_buffer above as non-null and in
-    // user-space.
-    let name_bytes = unsafe { slice::from_raw_parts(name_ptr as *const u8, name_len) };
-    let name = core::str::from_utf8(name_bytes).map_err(|_| SyscallError::InvalidArgument)?;
+    let name_bytes = copy_slice_from_user::<u8>(name_ptr, name_len)?;
+    let name = core::str::from_utf8(&name_bytes).map_err(|_| SyscallError::InvalidArgument)?;
 
     // Look up in the current process's env_vars
     let process = current_process().ok_or(SyscallError::InvalidState)?;
@@ -1016,12 +1095,8 @@ pub fn sys_getenv(
         }
 
         // Write value + NUL to user buffer
-        validate_user_buffer(buf_ptr, val_len + 1)?;
-        // SAFETY: buf_ptr validated by validate_user_buffer above as non-null and in
-        // user-space.
-        let buf = unsafe { slice::from_raw_parts_mut(buf_ptr as *mut u8, val_len + 1) };
-        buf[..val_len].copy_from_slice(value.as_bytes());
-        buf[val_len] = 0;
+        copy_bytes_to_user(buf_ptr, value.as_bytes())?;
+        copy_to_user(buf_ptr + val_len, &0u8)?;
 
         Ok(val_len)
     } else {
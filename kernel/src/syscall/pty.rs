@@ -27,7 +27,10 @@
 
 use alloc::{format, sync::Arc};
 
-use super::{validate_user_buffer, validate_user_ptr_typed, SyscallError, SyscallResult};
+use super::{
+    uaccess::{access_ok, copy_bytes_to_user, copy_from_user, copy_to_user, Access},
+    SyscallError, SyscallResult,
+};
 use crate::{
     fs::{
         file::{File, OpenFlags},
@@ -57,8 +60,8 @@ use crate::{
 /// `0` on success.
 pub fn sys_openpty(master_fd_ptr: usize, slave_fd_ptr: usize) -> SyscallResult {
     // Validate both output pointers before doing any allocation.
-    validate_user_ptr_typed::<i32>(master_fd_ptr)?;
-    validate_user_ptr_typed::<i32>(slave_fd_ptr)?;
+    access_ok(master_fd_ptr, core::mem::size_of::<i32>(), Access::Write)?;
+    access_ok(slave_fd_ptr, core::mem::size_of::<i32>(), Access::Write)?;
 
     // Allocate a new PTY pair through the global PtyManager.
     // create_pty() returns (master_id, slave_id); currently master_id == slave_id
@@ -96,13 +99,8 @@ pub fn sys_openpty(master_fd_ptr: usize, slave_fd_ptr: usize) -> SyscallResult {
     })?;
 
     // Write the fd numbers to user space.
-    // SAFETY: Both pointers were validated as aligned, non-null, and in user
-    // space by validate_user_ptr_typed above.  No other thread can alias
-    // these locations because they are caller-owned stack/heap slots.
-    unsafe {
-        core::ptr::write(master_fd_ptr as *mut i32, master_fd as i32);
-        core::ptr::write(slave_fd_ptr as *mut i32, slave_fd as i32);
-    }
+    copy_to_user(master_fd_ptr, &(master_fd as i32))?;
+    copy_to_user(slave_fd_ptr, &(slave_fd as i32))?;
 
     crate::println!(
         "[PTY] openpty: master_fd={}, slave_fd={}, pty_id={}",
@@ -191,7 +189,7 @@ pub fn sys_ptsname(master_fd: usize, buf_ptr: usize, buf_len: usize) -> SyscallR
     if buf_len == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_buffer(buf_ptr, buf_len)?;
+    access_ok(buf_ptr, buf_len, Access::Write)?;
 
     // Resolve the master fd to a File.
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -223,16 +221,9 @@ pub fn sys_ptsname(master_fd: usize, buf_ptr: usize, buf_len: usize) -> SyscallR
         return Err(SyscallError::InvalidArgument);
     }
 
-    // Copy path into user space.
-    // SAFETY: buf_ptr was validated as non-null and within user-space bounds
-    // covering buf_len bytes.  path_bytes.len() < buf_len so the write does
-    // not exceed the validated region.
-    unsafe {
-        let dst = buf_ptr as *mut u8;
-        core::ptr::copy_nonoverlapping(path_bytes.as_ptr(), dst, path_bytes.len());
-        // NUL terminate.
-        core::ptr::write(dst.add(path_bytes.len()), 0u8);
-    }
+    // Copy path into user space, NUL terminated.
+    copy_bytes_to_user(buf_ptr, path_bytes)?;
+    copy_to_user(buf_ptr + path_bytes.len(), &0u8)?;
 
     Ok(path_bytes.len())
 }
@@ -291,10 +282,6 @@ pub fn handle_pty_ioctl(master_fd: usize, cmd: usize, arg: usize) -> Option<Sysc
             if arg == 0 {
                 return Some(Err(SyscallError::InvalidPointer));
             }
-            if let Err(e) = validate_user_ptr_typed::<UserWinsize>(arg) {
-                return Some(Err(e));
-            }
-
             let ws =
                 with_pty_manager(|mgr| mgr.get_master(pty_id).map(|m| m.get_winsize())).flatten();
 
@@ -306,9 +293,7 @@ pub fn handle_pty_ioctl(master_fd: usize, cmd: usize, arg: usize) -> Option<Sysc
                         ws_xpixel: w.xpixel,
                         ws_ypixel: w.ypixel,
                     };
-                    // SAFETY: arg was validated above.
-                    unsafe { core::ptr::write(arg as *mut UserWinsize, user_ws) };
-                    Ok(0)
+                    copy_to_user(arg, &user_ws).map(|()| 0)
                 }
                 None => Err(SyscallError::ResourceNotFound),
             }
@@ -318,12 +303,10 @@ pub fn handle_pty_ioctl(master_fd: usize, cmd: usize, arg: usize) -> Option<Sysc
             if arg == 0 {
                 return Some(Err(SyscallError::InvalidPointer));
             }
-            if let Err(e) = validate_user_ptr_typed::<UserWinsize>(arg) {
-                return Some(Err(e));
-            }
-
-            // SAFETY: arg was validated above.
-            let user_ws = unsafe { core::ptr::read(arg as *const UserWinsize) };
+            let user_ws: UserWinsize = match copy_from_user(arg) {
+                Ok(ws) => ws,
+                Err(e) => return Some(Err(e)),
+            };
 
             let new_ws = crate::fs::pty::Winsize {
                 rows: user_ws.ws_row,
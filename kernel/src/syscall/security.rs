@@ -0,0 +1,184 @@
+//! Capability release and inspection, audit log, password hashing and
+//! syscall filter syscalls.
+
+use crate::{
+    cap::CapabilityToken,
+    process::{current_process, get_process, ProcessId},
+    security::{
+        auth,
+        cap_audit::{self, CapDenialRecord},
+        syscall_filter::Filter,
+    },
+    syscall::{
+        filesystem::read_user_path,
+        uaccess::{copy_bytes_to_user, copy_slice_from_user, copy_slice_to_user, copy_to_user},
+        SyscallError, SyscallResult,
+    },
+};
+
+/// Maximum number of records a single call may copy out.
+const MAX_RECORDS_PER_CALL: usize = 1024;
+
+/// Release a capability the caller holds (SYS_CAPABILITY_REVOKE = 31).
+///
+/// Removes the token from the caller's capability space only; copies
+/// delegated to other processes stay valid. This is what userland handle
+/// types call from `Drop`.
+///
+/// # Arguments
+/// - `token`: Capability token to release.
+pub fn sys_cap_release(token: usize) -> SyscallResult {
+    let caller = current_process().ok_or(SyscallError::InvalidState)?;
+    let cap_space = caller.capability_space.lock();
+    cap_space
+        .remove(CapabilityToken::from_u64(token as u64))
+        .map(|_| 0)
+        .ok_or(SyscallError::InvalidCapability)
+}
+
+/// Enumerate the capabilities held by a process (SYS_CAP_INSPECT = 32).
+///
+/// Unprivileged callers may only inspect themselves; uid 0 may inspect any
+/// process.
+///
+/// # Arguments
+/// - `pid`: Target process, or 0 for the caller.
+/// - `buf`: User buffer of `CapInfoRecord`s.
+/// - `max_entries`: Capacity of `buf` in records.
+///
+/// # Returns
+/// The total number of capabilities held, which may exceed `max_entries`
+/// (only the first `max_entries` are copied).
+pub fn sys_cap_inspect(pid: usize, buf: usize, max_entries: usize) -> SyscallResult {
+    let caller = current_process().ok_or(SyscallError::InvalidState)?;
+    let target_pid = if pid == 0 {
+        caller.pid
+    } else {
+        ProcessId(pid as u64)
+    };
+    if target_pid != caller.pid && caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let target = get_process(target_pid).ok_or(SyscallError::ProcessNotFound)?;
+    let records = {
+        let cap_space = target.capability_space.lock();
+        cap_audit::snapshot_capabilities(&cap_space)
+    };
+
+    let count = records.len().min(max_entries).min(MAX_RECORDS_PER_CALL);
+    copy_slice_to_user(buf, &records[..count])?;
+
+    Ok(records.len())
+}
+
+/// Read denied capability checks from the audit ring (SYS_AUDIT_READ = 33).
+///
+/// Restricted to uid 0 (the audit daemon).
+///
+/// # Arguments
+/// - `buf`: User buffer of `CapDenialRecord`s.
+/// - `max_records`: Capacity of `buf` in records.
+/// - `since_seq`: Only return records with a sequence number `>=` this. Pass
+///   the last seen sequence number plus one to resume; a gap in the returned
+///   sequence numbers means records were overwritten.
+///
+/// # Returns
+/// The number of records copied.
+pub fn sys_audit_read(buf: usize, max_records: usize, since_seq: usize) -> SyscallResult {
+    let caller = current_process().ok_or(SyscallError::InvalidState)?;
+    if caller.euid() != 0 {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let max = max_records.min(MAX_RECORDS_PER_CALL);
+    if max == 0 {
+        return Ok(0);
+    }
+    let mut records = alloc::vec![CapDenialRecord::default(); max];
+    let count = cap_audit::read_denials(since_seq as u64, &mut records);
+
+    copy_slice_to_user(buf, &records[..count])?;
+
+    Ok(count)
+}
+
+/// Hash a password in the style of `crypt(3)` (SYS_CRYPT = 359).
+///
+/// Exposes the kernel's PBKDF2-HMAC-SHA256 implementation so that `login`
+/// and `passwd` hash passwords the same way without carrying their own copy.
+/// See [`auth::crypt`] for the setting and output formats.
+///
+/// # Arguments
+/// - `key_ptr`: NUL-terminated password.
+/// - `setting_ptr`: NUL-terminated setting or stored hash string.
+/// - `out`: User buffer for the NUL-terminated hash string.
+/// - `out_len`: Capacity of `out` in bytes.
+///
+/// # Returns
+/// Length of the hash string, excluding the NUL.
+pub fn sys_crypt(key_ptr: usize, setting_ptr: usize, out: usize, out_len: usize) -> SyscallResult {
+    let mut key = read_user_path(key_ptr)?;
+    let setting = read_user_path(setting_ptr)?;
+
+    let mut hash = [0u8; auth::CRYPT_MAX_LEN];
+    let result = auth::crypt(key.as_bytes(), &setting, &mut hash);
+    // SAFETY: zeroing bytes keeps the string valid UTF-8.
+    unsafe { crate::crypto::constant_time::ct_zero(key.as_bytes_mut()) };
+    let len = result.map_err(|_| SyscallError::InvalidArgument)?;
+
+    if out_len < len + 1 {
+        return Err(SyscallError::InvalidArgument);
+    }
+    copy_bytes_to_user(out, &hash[..len])?;
+    copy_to_user(out + len, &0u8)?;
+
+    Ok(len)
+}
+
+/// Install a syscall filter (SYS_SYSCALL_FILTER = 383).
+///
+/// A process may filter itself, and a launcher its own children running
+/// under its effective uid; uid 0 may filter any process. See
+/// [`crate::security::syscall_filter`].
+///
+/// # Arguments
+/// - `pid`: Target process, or 0 for the caller.
+/// - `mode`: `MODE_ALLOW` or `MODE_DENY`, optionally `| MODE_ON_EXEC`.
+/// - `action`: `ACTION_KILL`, `ACTION_ERRNO` or `ACTION_LOG`.
+/// - `entries`: User array of `u32` syscall numbers and `GROUP | n` groups.
+/// - `count`: Number of entries.
+pub fn sys_syscall_filter(
+    pid: usize,
+    mode: usize,
+    action: usize,
+    entries: usize,
+    count: usize,
+) -> SyscallResult {
+    use veridian_abi::syscall_filter::{MAX_ENTRIES, MODE_ON_EXEC};
+
+    let caller = current_process().ok_or(SyscallError::InvalidState)?;
+    let target = if pid == 0 || pid as u64 == caller.pid.0 {
+        caller
+    } else {
+        let target = get_process(ProcessId(pid as u64)).ok_or(SyscallError::ProcessNotFound)?;
+        let own_child = target.parent == Some(caller.pid) && target.euid() == caller.euid();
+        if !own_child && caller.euid() != 0 {
+            return Err(SyscallError::PermissionDenied);
+        }
+        target
+    };
+
+    if count > MAX_ENTRIES {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let list = copy_slice_from_user::<u32>(entries, count)?;
+    let filter = Filter::new(mode as u32 & !MODE_ON_EXEC, action as u32, &list)
+        .map_err(|_| SyscallError::InvalidArgument)?;
+    target
+        .syscall_filters
+        .lock()
+        .add(filter, mode as u32 & MODE_ON_EXEC != 0)
+        .map_err(|_| SyscallError::ResourceLimitExceeded)?;
+    Ok(0)
+}
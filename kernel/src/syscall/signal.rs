@@ -6,7 +6,10 @@
 //! - `sys_sigsuspend` (122): Atomically set mask and suspend
 //! - `sys_sigreturn` (123): Return from signal trampoline
 
-use super::{validate_user_ptr_typed, SyscallError, SyscallResult};
+use super::{
+    uaccess::{copy_from_user, copy_to_user},
+    SyscallError, SyscallResult,
+};
 use crate::process;
 
 // ============================================================================
@@ -94,35 +97,29 @@ pub fn sys_sigaction(signum: usize, act_ptr: usize, oldact_ptr: usize) -> Syscal
         return Err(SyscallError::PermissionDenied);
     }
 
-    // Validate pointers if non-null
-    if act_ptr != 0 {
-        validate_user_ptr_typed::<SigAction>(act_ptr)?;
-    }
-    if oldact_ptr != 0 {
-        validate_user_ptr_typed::<SigAction>(oldact_ptr)?;
-    }
+    // Read the new action before writing the old one; they may alias
+    let new_act = if act_ptr != 0 {
+        Some(copy_from_user::<SigAction>(act_ptr)?)
+    } else {
+        None
+    };
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
 
     // Return the previous handler via oldact_ptr
     if oldact_ptr != 0 {
         let old_handler = proc.get_signal_handler(signum).unwrap_or(0);
-        // SAFETY: oldact_ptr was validated as non-null, in user-space, and
-        // aligned for SigAction above. We write the previous handler state.
-        unsafe {
-            let old_act = oldact_ptr as *mut SigAction;
-            (*old_act).sa_handler = old_handler as usize;
-            (*old_act).sa_flags = 0;
-            (*old_act).sa_restorer = 0;
-            (*old_act).sa_mask = 0;
-        }
+        let old_act = SigAction {
+            sa_handler: old_handler as usize,
+            sa_flags: 0,
+            sa_restorer: 0,
+            sa_mask: 0,
+        };
+        copy_to_user(oldact_ptr, &old_act)?;
     }
 
     // Install the new handler from act_ptr
-    if act_ptr != 0 {
-        // SAFETY: act_ptr was validated as non-null, in user-space, and
-        // aligned for SigAction above. We read the new handler address.
-        let new_act = unsafe { *(act_ptr as *const SigAction) };
+    if let Some(new_act) = new_act {
         proc.set_signal_handler(signum, new_act.sa_handler as u64)
             .map_err(|_| SyscallError::InvalidArgument)?;
     }
@@ -149,20 +146,12 @@ pub fn sys_sigprocmask(how: usize, set_ptr: usize, oldset_ptr: usize) -> Syscall
 
     // Write old mask to user space if requested
     if oldset_ptr != 0 {
-        validate_user_ptr_typed::<u64>(oldset_ptr)?;
-        // SAFETY: oldset_ptr was validated as non-null, in user-space, and
-        // aligned for u64 above. We write the previous signal mask.
-        unsafe {
-            *(oldset_ptr as *mut u64) = old_mask;
-        }
+        copy_to_user(oldset_ptr, &old_mask)?;
     }
 
     // Apply new mask if a set pointer was provided
     if set_ptr != 0 {
-        validate_user_ptr_typed::<u64>(set_ptr)?;
-        // SAFETY: set_ptr was validated as non-null, in user-space, and
-        // aligned for u64 above. We read the new mask value.
-        let new_bits = unsafe { *(set_ptr as *const u64) };
+        let new_bits: u64 = copy_from_user(set_ptr)?;
 
         let updated_mask = match how {
             SIG_BLOCK => old_mask | new_bits,
@@ -194,13 +183,10 @@ pub fn sys_sigsuspend(mask_ptr: usize) -> SyscallResult {
     if mask_ptr == 0 {
         return Err(SyscallError::InvalidArgument);
     }
-    validate_user_ptr_typed::<u64>(mask_ptr)?;
+    let temp_mask: u64 = copy_from_user(mask_ptr)?;
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
 
-    // SAFETY: mask_ptr was validated above.
-    let temp_mask = unsafe { *(mask_ptr as *const u64) };
-
     // Save current mask and apply temporary mask
     let old_mask = proc.get_signal_mask();
     let sanitized = temp_mask & !((1u64 << 9) | (1u64 << 19));
@@ -10,7 +10,10 @@ use crate::{
     arch::context::ThreadContext,
     process::{self, thread::ThreadBuilder, ProcessState},
     sched,
-    syscall::{userspace::validate_user_ptr, SyscallError},
+    syscall::{
+        uaccess::{access_ok, copy_to_user, Access, USER_SPACE_END},
+        SyscallError,
+    },
 };
 
 // Allowed clone flags (subset of Linux, thread-oriented)
@@ -24,12 +27,6 @@ const CLONE_PARENT_SETTID: usize = 0x0010_0000;
 const CLONE_CHILD_CLEARTID: usize = 0x0020_0000;
 const CLONE_CHILD_SETTID: usize = 0x0100_0000;
 
-/// Upper bound of the user-space address range.  Any stack pointer must be
-/// strictly below this address.  The value matches the canonical user-space
-/// limit on x86_64; AArch64 and RISC-V use the same logical limit via
-/// `validate_user_ptr`.
-const USER_SPACE_END: usize = 0x0000_8000_0000_0000;
-
 /// Create a new thread sharing the current process's address space and
 /// resources, following the Linux `clone(2)` semantics for thread creation.
 ///
@@ -107,13 +104,13 @@ pub fn sys_thread_clone(
 
     // Validate TID pointers if requested
     if flags & CLONE_PARENT_SETTID != 0 {
-        validate_user_ptr(parent_tid_ptr as *const u32, core::mem::size_of::<u32>())?;
+        access_ok(parent_tid_ptr, core::mem::size_of::<u32>(), Access::Write)?;
     }
     if flags & CLONE_CHILD_SETTID != 0 {
-        validate_user_ptr(child_tid_ptr as *const u32, core::mem::size_of::<u32>())?;
+        access_ok(child_tid_ptr, core::mem::size_of::<u32>(), Access::Write)?;
     }
     if flags & CLONE_CHILD_CLEARTID != 0 {
-        validate_user_ptr(child_tid_ptr as *const u32, core::mem::size_of::<u32>())?;
+        access_ok(child_tid_ptr, core::mem::size_of::<u32>(), Access::Write)?;
     }
 
     let proc = process::current_process().ok_or(SyscallError::InvalidState)?;
@@ -197,13 +194,7 @@ pub fn sys_thread_clone(
     // (before the child is scheduled) so the parent can observe the TID
     // immediately after clone returns.
     if flags & CLONE_PARENT_SETTID != 0 {
-        // SAFETY: `parent_tid_ptr` was validated above via `validate_user_ptr`.
-        // `copy_to_user` performs its own bounds check as a defence-in-depth
-        // measure.  The write is a single u32 which is naturally aligned.
-        unsafe {
-            crate::syscall::userspace::copy_to_user(parent_tid_ptr, &(tid.0 as u32))
-                .map_err(|_| SyscallError::InvalidPointer)?;
-        }
+        copy_to_user(parent_tid_ptr, &(tid.0 as u32))?;
     }
 
     // CLONE_CHILD_SETTID: write the child's TID into `child_tid_ptr`.
@@ -214,12 +205,8 @@ pub fn sys_thread_clone(
     // spaces) this would need to be deferred to the child's first
     // scheduling quantum.
     if flags & CLONE_CHILD_SETTID != 0 {
-        // SAFETY: `child_tid_ptr` was validated above via `validate_user_ptr`.
         // The pointer targets shared user memory (CLONE_VM is set).
-        unsafe {
-            crate::syscall::userspace::copy_to_user(child_tid_ptr, &(tid.0 as u32))
-                .map_err(|_| SyscallError::InvalidPointer)?;
-        }
+        copy_to_user(child_tid_ptr, &(tid.0 as u32))?;
     }
 
     // TODO(tier7): CLONE_CHILD_CLEARTID is registered via `builder.clear_tid()`
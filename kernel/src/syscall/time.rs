@@ -5,7 +5,10 @@
 //! and software timer creation/cancellation.
 //! All operations delegate to the [`crate::timer`] subsystem.
 
-use super::{validate_user_ptr_typed, SyscallError, SyscallResult};
+use super::{
+    uaccess::{copy_from_user, copy_to_user},
+    SyscallError, SyscallResult,
+};
 
 /// Get monotonic uptime in milliseconds (SYS_TIME_GET_UPTIME = 100)
 ///
@@ -99,29 +102,25 @@ struct Timeval {
 /// # Returns
 /// 0 on success.
 pub fn sys_clock_gettime(clock_id: usize, tp_ptr: usize) -> SyscallResult {
-    validate_user_ptr_typed::<Timespec>(tp_ptr)?;
-
-    let uptime_ms = crate::timer::get_uptime_ms();
+    // Same counter as the vDSO fast path, so the two never disagree
+    let (secs, nsecs) = crate::process::vdso::monotonic_time();
 
     let ts = match clock_id {
         CLOCK_MONOTONIC => Timespec {
-            tv_sec: (uptime_ms / 1000) as i64,
-            tv_nsec: ((uptime_ms % 1000) * 1_000_000) as i64,
+            tv_sec: secs as i64,
+            tv_nsec: nsecs as i64,
         },
         CLOCK_REALTIME => {
             // Realtime = monotonic (no RTC yet, epoch starts at boot)
             Timespec {
-                tv_sec: (uptime_ms / 1000) as i64,
-                tv_nsec: ((uptime_ms % 1000) * 1_000_000) as i64,
+                tv_sec: secs as i64,
+                tv_nsec: nsecs as i64,
             }
         }
         _ => return Err(SyscallError::InvalidArgument),
     };
 
-    // SAFETY: tp_ptr was validated as aligned, non-null, and in user space.
-    unsafe {
-        core::ptr::write(tp_ptr as *mut Timespec, ts);
-    }
+    copy_to_user(tp_ptr, &ts)?;
     Ok(0)
 }
 
@@ -140,16 +139,12 @@ pub fn sys_clock_getres(clock_id: usize, res_ptr: usize) -> SyscallResult {
     }
 
     if res_ptr != 0 {
-        validate_user_ptr_typed::<Timespec>(res_ptr)?;
         // Timer resolution is 1ms (hardware timer tick granularity)
         let res = Timespec {
             tv_sec: 0,
             tv_nsec: 1_000_000, // 1ms in nanoseconds
         };
-        // SAFETY: res_ptr was validated above.
-        unsafe {
-            core::ptr::write(res_ptr as *mut Timespec, res);
-        }
+        copy_to_user(res_ptr, &res)?;
     }
     Ok(0)
 }
@@ -165,10 +160,7 @@ pub fn sys_clock_getres(clock_id: usize, res_ptr: usize) -> SyscallResult {
 /// # Returns
 /// 0 on success.
 pub fn sys_nanosleep(req_ptr: usize, rem_ptr: usize) -> SyscallResult {
-    validate_user_ptr_typed::<Timespec>(req_ptr)?;
-
-    // SAFETY: req_ptr was validated as aligned, non-null, and in user space.
-    let req = unsafe { core::ptr::read(req_ptr as *const Timespec) };
+    let req: Timespec = copy_from_user(req_ptr)?;
 
     if req.tv_sec < 0 || req.tv_nsec < 0 || req.tv_nsec >= 1_000_000_000 {
         return Err(SyscallError::InvalidArgument);
@@ -184,15 +176,11 @@ pub fn sys_nanosleep(req_ptr: usize, rem_ptr: usize) -> SyscallResult {
 
     // Write zero remaining time
     if rem_ptr != 0 {
-        validate_user_ptr_typed::<Timespec>(rem_ptr)?;
         let zero = Timespec {
             tv_sec: 0,
             tv_nsec: 0,
         };
-        // SAFETY: rem_ptr was validated above.
-        unsafe {
-            core::ptr::write(rem_ptr as *mut Timespec, zero);
-        }
+        copy_to_user(rem_ptr, &zero)?;
     }
 
     Ok(0)
@@ -210,7 +198,6 @@ pub fn sys_gettimeofday(tv_ptr: usize, _tz_ptr: usize) -> SyscallResult {
     if tv_ptr == 0 {
         return Err(SyscallError::InvalidPointer);
     }
-    validate_user_ptr_typed::<Timeval>(tv_ptr)?;
 
     let uptime_ms = crate::timer::get_uptime_ms();
     let tv = Timeval {
@@ -218,9 +205,201 @@ pub fn sys_gettimeofday(tv_ptr: usize, _tz_ptr: usize) -> SyscallResult {
         tv_usec: ((uptime_ms % 1000) * 1000) as i64,
     };
 
-    // SAFETY: tv_ptr was validated above.
-    unsafe {
-        core::ptr::write(tv_ptr as *mut Timeval, tv);
+    copy_to_user(tv_ptr, &tv)?;
+    Ok(0)
+}
+
+// ============================================================================
+// Interval timers and resource usage
+// ============================================================================
+
+/// POSIX itimerval structure layout (matches C struct itimerval).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Itimerval {
+    it_interval: Timeval,
+    it_value: Timeval,
+}
+
+/// Linux-compatible struct rusage. Only the CPU times and `ru_maxrss` are
+/// tracked; the remaining counters are reported as zero.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Rusage {
+    ru_utime: Timeval,
+    ru_stime: Timeval,
+    ru_maxrss: i64,
+    ru_unused: [i64; 13],
+}
+
+/// Linux-compatible struct tms, in clock ticks.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Tms {
+    tms_utime: i64,
+    tms_stime: i64,
+    tms_cutime: i64,
+    tms_cstime: i64,
+}
+
+const RUSAGE_SELF: isize = 0;
+const RUSAGE_CHILDREN: isize = -1;
+const RUSAGE_THREAD: isize = 1;
+
+impl Timeval {
+    fn from_us(us: u64) -> Self {
+        Self {
+            tv_sec: (us / 1_000_000) as i64,
+            tv_usec: (us % 1_000_000) as i64,
+        }
+    }
+
+    fn to_us(self) -> Result<u64, SyscallError> {
+        if self.tv_sec < 0 || !(0..1_000_000).contains(&self.tv_usec) {
+            return Err(SyscallError::InvalidArgument);
+        }
+        Ok((self.tv_sec as u64)
+            .saturating_mul(1_000_000)
+            .saturating_add(self.tv_usec as u64))
+    }
+}
+
+fn write_itimerval(ptr: usize, (value_us, interval_us): (u64, u64)) -> Result<(), SyscallError> {
+    let val = Itimerval {
+        it_interval: Timeval::from_us(interval_us),
+        it_value: Timeval::from_us(value_us),
+    };
+    copy_to_user(ptr, &val)?;
+    Ok(())
+}
+
+/// Get the value of an interval timer (SYS_GETITIMER = 361).
+///
+/// # Arguments
+/// - `which`: ITIMER_REAL (0), ITIMER_VIRTUAL (1) or ITIMER_PROF (2).
+/// - `curr_ptr`: User-space pointer to a `struct itimerval`.
+///
+/// # Returns
+/// 0 on success.
+pub fn sys_getitimer(which: usize, curr_ptr: usize) -> SyscallResult {
+    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let current = process
+        .itimers
+        .get(which)
+        .ok_or(SyscallError::InvalidArgument)?;
+    write_itimerval(curr_ptr, current)?;
+    Ok(0)
+}
+
+/// Arm or disarm an interval timer (SYS_SETITIMER = 360).
+///
+/// Expiry of ITIMER_REAL, ITIMER_VIRTUAL and ITIMER_PROF raises SIGALRM,
+/// SIGVTALRM and SIGPROF respectively. A zero `it_value` disarms the timer;
+/// a non-zero `it_interval` makes it periodic.
+///
+/// # Arguments
+/// - `which`: ITIMER_REAL (0), ITIMER_VIRTUAL (1) or ITIMER_PROF (2).
+/// - `new_ptr`: User-space pointer to the new `struct itimerval`.
+/// - `old_ptr`: User-space pointer receiving the previous value (may be NULL).
+///
+/// # Returns
+/// 0 on success.
+pub fn sys_setitimer(which: usize, new_ptr: usize, old_ptr: usize) -> SyscallResult {
+    use crate::process::itimer::ITIMER_REAL;
+
+    let new: Itimerval = copy_from_user(new_ptr)?;
+    let value_us = new.it_value.to_us()?;
+    let interval_us = new.it_interval.to_us()?;
+
+    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    let old = process
+        .itimers
+        .set(which, value_us, interval_us)
+        .ok_or(SyscallError::InvalidArgument)?;
+    if which == ITIMER_REAL && value_us != 0 {
+        crate::process::itimer::watch_real(process.pid);
+    }
+
+    if old_ptr != 0 {
+        write_itimerval(old_ptr, old)?;
     }
     Ok(0)
 }
+
+/// Get resource usage (SYS_GETRUSAGE = 362).
+///
+/// # Arguments
+/// - `who`: RUSAGE_SELF (0), RUSAGE_CHILDREN (-1) or RUSAGE_THREAD (1).
+/// - `usage_ptr`: User-space pointer to a `struct rusage`.
+///
+/// # Returns
+/// 0 on success.
+pub fn sys_getrusage(who: usize, usage_ptr: usize) -> SyscallResult {
+    use core::sync::atomic::Ordering;
+
+    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+
+    let (user_us, system_us) = match who as isize {
+        RUSAGE_SELF => (
+            process.user_time.load(Ordering::Relaxed),
+            process.system_time.load(Ordering::Relaxed),
+        ),
+        RUSAGE_CHILDREN => (
+            process.children_user_time.load(Ordering::Relaxed),
+            process.children_system_time.load(Ordering::Relaxed),
+        ),
+        RUSAGE_THREAD => {
+            let thread = crate::process::current_thread().ok_or(SyscallError::InvalidState)?;
+            (
+                thread.user_time.load(Ordering::Relaxed),
+                thread.system_time.load(Ordering::Relaxed),
+            )
+        }
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+    let maxrss_kb = if who as isize == RUSAGE_CHILDREN {
+        0
+    } else {
+        process.memory_stats.resident_size.load(Ordering::Relaxed) / 1024
+    };
+
+    let usage = Rusage {
+        ru_utime: Timeval::from_us(user_us),
+        ru_stime: Timeval::from_us(system_us),
+        ru_maxrss: maxrss_kb as i64,
+        ru_unused: [0; 13],
+    };
+    copy_to_user(usage_ptr, &usage)?;
+    Ok(0)
+}
+
+/// Get process times (SYS_TIMES = 381).
+///
+/// Fills the caller's `struct tms` with its own and its reaped children's
+/// user and system time, in clock ticks
+/// ([`crate::process::itimer::CLOCK_TICKS_PER_SEC`]).
+///
+/// # Arguments
+/// - `tms_ptr`: User-space pointer to a `struct tms` (may be NULL).
+///
+/// # Returns
+/// Clock ticks since boot.
+pub fn sys_times(tms_ptr: usize) -> SyscallResult {
+    use core::sync::atomic::Ordering;
+
+    use crate::process::itimer::{us_to_clock_ticks, CLOCK_TICKS_PER_SEC};
+
+    let process = crate::process::current_process().ok_or(SyscallError::InvalidState)?;
+    if tms_ptr != 0 {
+        let ticks =
+            |t: &core::sync::atomic::AtomicU64| us_to_clock_ticks(t.load(Ordering::Relaxed)) as i64;
+        let tms = Tms {
+            tms_utime: ticks(&process.user_time),
+            tms_stime: ticks(&process.system_time),
+            tms_cutime: ticks(&process.children_user_time),
+            tms_cstime: ticks(&process.children_system_time),
+        };
+        copy_to_user(tms_ptr, &tms)?;
+    }
+    Ok((crate::arch::timer::get_timestamp_ms() * CLOCK_TICKS_PER_SEC / 1000) as usize)
+}
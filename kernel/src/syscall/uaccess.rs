@@ -0,0 +1,356 @@
+//! Copy-in/copy-out of user memory for syscall handlers
+//!
+//! Syscall handlers never dereference user pointers themselves. They copy
+//! arguments in with [`copy_from_user`], [`copy_slice_from_user`] or
+//! [`strncpy_from_user`] and results out with [`copy_to_user`] or
+//! [`copy_slice_to_user`]. Every copy:
+//!
+//! 1. Rejects null pointers, ranges that wrap around or reach past
+//!    [`USER_SPACE_END`], and lengths over [`MAX_BUFFER_SIZE`].
+//! 2. Checks the range against the calling process's address space: each byte
+//!    must lie in a user mapping, and in a writable one for copy-out.
+//! 3. Opens a SMAP/PAN window only for the duration of the copy.
+//! 4. On x86_64 copies through a routine whose page faults are recoverable, so
+//!    a mapping that disappears between the check and the copy fails the
+//!    syscall with `InvalidPointer` (EFAULT) instead of panicking the kernel.
+//!    AArch64 and RISC-V have no kernel fault recovery yet and rely on step 2.
+//!
+//! All failures are reported as [`SyscallError::InvalidPointer`], except an
+//! oversized length which is [`SyscallError::InvalidArgument`].
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::mem::{size_of, MaybeUninit};
+
+use super::SyscallError;
+
+/// First address above user space.
+///
+/// On x86_64 this is the end of the lower canonical half (128 TB).
+/// AArch64 and RISC-V use the same split on the QEMU virt machine.
+pub const USER_SPACE_END: usize = 0x0000_8000_0000_0000;
+
+/// Largest single copy a syscall may request (256 MB).
+pub const MAX_BUFFER_SIZE: usize = 256 * 1024 * 1024;
+
+/// Default limit for [`strncpy_from_user`] callers reading paths.
+pub const PATH_MAX: usize = 4096;
+
+const PAGE_SIZE: usize = 4096;
+
+/// Direction of a user memory access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// The kernel reads the range (copy-in).
+    Read,
+    /// The kernel writes the range (copy-out).
+    Write,
+}
+
+/// Check that `[ptr, ptr + len)` may be accessed on behalf of the current
+/// process.
+///
+/// Copies validate on their own; handlers only call this directly when they
+/// hand the address to other code instead of copying (e.g. futex keys).
+pub fn access_ok(ptr: usize, len: usize, access: Access) -> Result<(), SyscallError> {
+    user_range(ptr, len)?;
+    if len == 0 {
+        return Ok(());
+    }
+    check_address_space(ptr, len, access)
+}
+
+/// Check that `[ptr, ptr + len)` is a non-null range below
+/// [`USER_SPACE_END`], without looking at the address space.
+///
+/// For syscalls that operate on address ranges rather than their contents
+/// (munmap, mprotect, madvise), where unmapped pages are not an error.
+pub fn user_range(ptr: usize, len: usize) -> Result<(), SyscallError> {
+    if ptr == 0 {
+        return Err(SyscallError::InvalidPointer);
+    }
+    if len > MAX_BUFFER_SIZE {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let end = ptr.checked_add(len).ok_or(SyscallError::InvalidPointer)?;
+    if end > USER_SPACE_END {
+        return Err(SyscallError::InvalidPointer);
+    }
+    Ok(())
+}
+
+/// Check the range against the current process's mappings. Without a
+/// current process (early boot, kernel threads) only the range checks of
+/// [`access_ok`] apply.
+#[cfg(feature = "alloc")]
+fn check_address_space(ptr: usize, len: usize, access: Access) -> Result<(), SyscallError> {
+    let Some(process) = crate::process::current_process() else {
+        return Ok(());
+    };
+    let memory_space = process.memory_space.lock();
+    if memory_space.user_range_accessible(ptr, len, access == Access::Write) {
+        Ok(())
+    } else {
+        Err(SyscallError::InvalidPointer)
+    }
+}
+
+#[cfg(not(feature = "alloc"))]
+fn check_address_space(_ptr: usize, _len: usize, _access: Access) -> Result<(), SyscallError> {
+    Ok(())
+}
+
+/// Copy `len` bytes with user access enabled. Fails if the copy faulted.
+///
+/// # Safety
+///
+/// The kernel side of the copy must be valid for `len` bytes; the user side
+/// must have passed [`access_ok`].
+unsafe fn raw_copy(dst: *mut u8, src: *const u8, len: usize) -> Result<(), SyscallError> {
+    let _smap = crate::security::smep_smap::SmapGuard::new();
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: Forwarded to the caller. Faults on the user side are caught by
+    // the page fault handler and reported as uncopied bytes.
+    let uncopied = unsafe { crate::arch::x86_64::uaccess::copy(dst, src, len) };
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let uncopied = {
+        // SAFETY: Forwarded to the caller; the user range is mapped.
+        unsafe { core::ptr::copy_nonoverlapping(src, dst, len) };
+        0
+    };
+
+    if uncopied == 0 {
+        Ok(())
+    } else {
+        Err(SyscallError::InvalidPointer)
+    }
+}
+
+/// Fill `dst` from user memory at `ptr`.
+pub fn copy_bytes_from_user(ptr: usize, dst: &mut [u8]) -> Result<(), SyscallError> {
+    if dst.is_empty() {
+        return Ok(());
+    }
+    access_ok(ptr, dst.len(), Access::Read)?;
+    // SAFETY: `dst` is a kernel buffer of the copied length and the user
+    // range was validated above.
+    unsafe { raw_copy(dst.as_mut_ptr(), ptr as *const u8, dst.len()) }
+}
+
+/// Copy `src` to user memory at `ptr`.
+pub fn copy_bytes_to_user(ptr: usize, src: &[u8]) -> Result<(), SyscallError> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    access_ok(ptr, src.len(), Access::Write)?;
+    // SAFETY: `src` is a kernel buffer of the copied length and the user
+    // range was validated above.
+    unsafe { raw_copy(ptr as *mut u8, src.as_ptr(), src.len()) }
+}
+
+/// Read a `T` from user memory. `ptr` need not be aligned.
+///
+/// `T` must be plain old data: every bit pattern of `size_of::<T>()` bytes
+/// has to be a valid `T` (integers and `#[repr(C)]` structs of them).
+pub fn copy_from_user<T: Copy>(ptr: usize) -> Result<T, SyscallError> {
+    let mut value = MaybeUninit::<T>::uninit();
+    if size_of::<T>() > 0 {
+        access_ok(ptr, size_of::<T>(), Access::Read)?;
+        // SAFETY: `value` provides size_of::<T>() writable bytes and the user
+        // range was validated above.
+        unsafe { raw_copy(value.as_mut_ptr().cast(), ptr as *const u8, size_of::<T>())? };
+    }
+    // SAFETY: All bytes were initialized by the copy, and `T` is plain old
+    // data per this function's contract.
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Write `value` to user memory. `ptr` need not be aligned.
+pub fn copy_to_user<T: Copy>(ptr: usize, value: &T) -> Result<(), SyscallError> {
+    if size_of::<T>() == 0 {
+        return Ok(());
+    }
+    access_ok(ptr, size_of::<T>(), Access::Write)?;
+    // SAFETY: `value` is a valid `T` of size_of::<T>() bytes and the user
+    // range was validated above.
+    unsafe { raw_copy(ptr as *mut u8, (value as *const T).cast(), size_of::<T>()) }
+}
+
+/// Read an array of `count` elements from user memory.
+///
+/// `T` must be plain old data, as for [`copy_from_user`].
+#[cfg(feature = "alloc")]
+pub fn copy_slice_from_user<T: Copy>(ptr: usize, count: usize) -> Result<Vec<T>, SyscallError> {
+    let len = count
+        .checked_mul(size_of::<T>())
+        .ok_or(SyscallError::InvalidArgument)?;
+    // Validate before allocating: `count` comes from the caller.
+    if len > 0 {
+        access_ok(ptr, len, Access::Read)?;
+    }
+    let mut items = Vec::<T>::with_capacity(count);
+    if len > 0 {
+        // SAFETY: The vector has capacity for `count` elements (`len` bytes)
+        // and the user range was validated above.
+        unsafe { raw_copy(items.as_mut_ptr().cast(), ptr as *const u8, len)? };
+    }
+    // SAFETY: The copy initialized all `count` elements; `T` is plain old
+    // data per this function's contract.
+    unsafe { items.set_len(count) };
+    Ok(items)
+}
+
+/// Write an array of elements to user memory.
+pub fn copy_slice_to_user<T: Copy>(ptr: usize, items: &[T]) -> Result<(), SyscallError> {
+    let len = core::mem::size_of_val(items);
+    if len == 0 {
+        return Ok(());
+    }
+    access_ok(ptr, len, Access::Write)?;
+    // SAFETY: `items` spans `len` valid bytes and the user range was
+    // validated above.
+    unsafe { raw_copy(ptr as *mut u8, items.as_ptr().cast(), len) }
+}
+
+/// Read a NUL-terminated UTF-8 string of at most `max_len` bytes (not
+/// counting the NUL) from user memory.
+///
+/// The string is copied page by page, so a string ending just before an
+/// unmapped page is read correctly. Fails with `InvalidArgument` if there is
+/// no NUL within `max_len + 1` bytes or the bytes are not UTF-8.
+#[cfg(feature = "alloc")]
+pub fn strncpy_from_user(ptr: usize, max_len: usize) -> Result<String, SyscallError> {
+    let mut bytes = Vec::new();
+    let mut addr = ptr;
+    let mut chunk = [0u8; 256];
+    loop {
+        // Never let a chunk cross a page boundary: the page holding the
+        // terminator may be the last mapped one.
+        let to_page_end = PAGE_SIZE - (addr % PAGE_SIZE);
+        let want = chunk.len().min(to_page_end).min(max_len + 1 - bytes.len());
+        copy_bytes_from_user(addr, &mut chunk[..want])?;
+        if let Some(nul) = chunk[..want].iter().position(|&b| b == 0) {
+            bytes.extend_from_slice(&chunk[..nul]);
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..want]);
+        if bytes.len() > max_len {
+            return Err(SyscallError::InvalidArgument);
+        }
+        addr += want;
+    }
+    String::from_utf8(bytes).map_err(|_| SyscallError::InvalidArgument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_ok_rejects_bad_ranges() {
+        assert_eq!(
+            access_ok(0, 8, Access::Read),
+            Err(SyscallError::InvalidPointer)
+        );
+        assert_eq!(
+            access_ok(usize::MAX - 4, 8, Access::Read),
+            Err(SyscallError::InvalidPointer)
+        );
+        assert_eq!(
+            access_ok(USER_SPACE_END - 4, 8, Access::Write),
+            Err(SyscallError::InvalidPointer)
+        );
+        assert_eq!(
+            access_ok(0x1000, MAX_BUFFER_SIZE + 1, Access::Read),
+            Err(SyscallError::InvalidArgument)
+        );
+        assert_eq!(access_ok(USER_SPACE_END - 8, 8, Access::Read), Ok(()));
+    }
+
+    #[test]
+    fn test_copy_rejects_kernel_addresses() {
+        assert_eq!(
+            copy_from_user::<u64>(0xFFFF_8000_0000_1000),
+            Err(SyscallError::InvalidPointer)
+        );
+        assert_eq!(
+            copy_to_user(0xFFFF_8000_0000_1000, &1u32),
+            Err(SyscallError::InvalidPointer)
+        );
+        assert_eq!(
+            copy_slice_to_user(0, &[1u8, 2, 3]),
+            Err(SyscallError::InvalidPointer)
+        );
+    }
+
+    #[test]
+    fn test_empty_copies_skip_validation() {
+        assert_eq!(copy_bytes_from_user(0, &mut []), Ok(()));
+        assert_eq!(copy_slice_to_user::<u32>(0, &[]), Ok(()));
+        assert_eq!(copy_slice_from_user::<u32>(0, 0), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_slice_from_user_rejects_huge_count() {
+        assert_eq!(
+            copy_slice_from_user::<u8>(0x1000, usize::MAX / 2),
+            Err(SyscallError::InvalidArgument)
+        );
+        assert_eq!(
+            copy_slice_from_user::<u64>(0x1000, usize::MAX / 2),
+            Err(SyscallError::InvalidArgument)
+        );
+    }
+
+    // The remaining tests run on the host, where heap addresses fall in the
+    // lower half and there is no current process, so "user" memory is just a
+    // local buffer.
+
+    #[test]
+    fn test_typed_round_trip_unaligned() {
+        let mut buf = [0u8; 16];
+        let ptr = buf.as_mut_ptr() as usize + 1;
+        copy_to_user(ptr, &0x1122_3344_5566_7788u64).unwrap();
+        assert_eq!(copy_from_user::<u64>(ptr), Ok(0x1122_3344_5566_7788));
+        assert_eq!(buf[1], 0x88);
+    }
+
+    #[test]
+    fn test_slice_round_trip() {
+        let mut buf = [0u32; 4];
+        copy_slice_to_user(buf.as_mut_ptr() as usize, &[1u32, 2, 3]).unwrap();
+        assert_eq!(buf, [1, 2, 3, 0]);
+        assert_eq!(
+            copy_slice_from_user::<u32>(buf.as_ptr() as usize, 4),
+            Ok(alloc::vec![1, 2, 3, 0])
+        );
+    }
+
+    #[test]
+    fn test_strncpy_from_user() {
+        let text = b"/bin/vsh\0trailing";
+        let ptr = text.as_ptr() as usize;
+        assert_eq!(strncpy_from_user(ptr, 64).as_deref(), Ok("/bin/vsh"));
+        assert_eq!(strncpy_from_user(ptr, 8).as_deref(), Ok("/bin/vsh"));
+        assert_eq!(
+            strncpy_from_user(ptr, 7),
+            Err(SyscallError::InvalidArgument)
+        );
+
+        let long = [b'a'; 600];
+        assert_eq!(
+            strncpy_from_user(long.as_ptr() as usize, 599),
+            Err(SyscallError::InvalidArgument)
+        );
+
+        let invalid = [0xFFu8, 0xFE, 0];
+        assert_eq!(
+            strncpy_from_user(invalid.as_ptr() as usize, 16),
+            Err(SyscallError::InvalidArgument)
+        );
+    }
+}
@@ -1,13 +1,21 @@
-//! User space memory access utilities
+//! User space argument vectors
 //!
-//! Safe functions for copying data between kernel and user space.
+//! Copies argv/envp style string arrays out of user space for execve,
+//! enforcing ARG_MAX. Single values, buffers and strings go through
+//! [`super::uaccess`].
 
-use core::{slice, str};
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
 
-use super::SyscallError;
+use super::{
+    uaccess::{copy_from_user, strncpy_from_user},
+    SyscallError,
+};
 
 /// Maximum string length we'll copy from user space
-const MAX_USER_STRING_LEN: usize = 4096;
+const MAX_USER_STRING_LEN: usize = 4095;
 
 /// Maximum combined size of argv + envp data passed to execve (128 KB).
 /// Matches ARG_MAX in userland/libc/include/limits.h.
@@ -18,162 +26,13 @@ const ARG_MAX: usize = 131072;
 /// of tiny strings that would individually pass size checks.
 const MAX_ARGS: usize = 32768;
 
-/// User space memory range constants
-const USER_SPACE_START: usize = 0x0000_0000_0000_0000;
-const USER_SPACE_END: usize = 0x0000_7FFF_FFFF_FFFF; // 128TB
-const PAGE_SIZE: usize = 4096;
-
-/// Check if a user pointer is valid with comprehensive validation
-pub fn validate_user_ptr<T>(ptr: *const T, len: usize) -> Result<(), SyscallError> {
-    let addr = ptr as usize;
-
-    // Check for null pointer
-    if addr == 0 {
-        return Err(SyscallError::InvalidPointer);
-    }
-
-    // Calculate end address and check for overflow
-    let end = addr.checked_add(len).ok_or(SyscallError::InvalidPointer)?;
-
-    // Check address range is within user space
-    // Note: USER_SPACE_START is 0, so we only need to check the upper bound
-    if end > USER_SPACE_END {
-        return Err(SyscallError::InvalidPointer);
-    }
-
-    // Validate page mappings for the entire range
-    validate_page_mappings(addr, end)?;
-
-    Ok(())
-}
-
-/// Validate that all pages in the given range are mapped and accessible
-fn validate_page_mappings(start: usize, end: usize) -> Result<(), SyscallError> {
-    // Range check: verify all pages fall within user space.
-    // Note: translate_user_address() walks page tables using raw physical
-    // addresses as pointers, which requires identity mapping. Since syscalls
-    // run with user CR3 (no identity mapping), the page table walk faults.
-    // The range check is sufficient: if a page is truly unmapped, the
-    // subsequent volatile read/write will trigger a proper page fault.
-    for page_addr in (start..end).step_by(PAGE_SIZE) {
-        if !crate::mm::is_user_addr_valid(page_addr) {
-            return Err(SyscallError::UnmappedMemory);
-        }
-    }
-
-    if !end.is_multiple_of(PAGE_SIZE) {
-        let last_page = (end - 1) & !(PAGE_SIZE - 1);
-        if !crate::mm::is_user_addr_valid(last_page) {
-            return Err(SyscallError::UnmappedMemory);
-        }
-    }
-
-    Ok(())
-}
-
-/// Check if a user pointer is valid (compatibility wrapper)
-pub fn validate_user_ptr_compat(ptr: usize, size: usize) -> Result<(), SyscallError> {
-    validate_user_ptr(ptr as *const u8, size)
-}
-
 /// Copy a null-terminated string from user space
-///
-/// # Safety
-/// This function reads from user-provided pointers and must validate them
-pub unsafe fn copy_string_from_user(user_ptr: usize) -> Result<String, SyscallError> {
-    validate_user_ptr(user_ptr as *const u8, 1)?;
-
-    // Find string length by looking for null terminator
-    let mut len = 0;
-    let mut ptr = user_ptr as *const u8;
-
-    while len < MAX_USER_STRING_LEN {
-        // Validate each page as we cross boundaries
-        if len % 4096 == 0 {
-            validate_user_ptr(ptr, 1)?;
-        }
-
-        let byte = ptr::read_volatile(ptr);
-        if byte == 0 {
-            break;
-        }
-
-        len += 1;
-        ptr = ptr.offset(1);
-    }
-
-    if len >= MAX_USER_STRING_LEN {
-        return Err(SyscallError::InvalidArgument);
-    }
-
-    // Copy the string
-    let slice = slice::from_raw_parts(user_ptr as *const u8, len);
-    use alloc::string::String;
-    let string = String::from(str::from_utf8(slice).map_err(|_| SyscallError::InvalidArgument)?);
-
-    Ok(string)
-}
-
-/// Copy data from user space to kernel space
-///
-/// # Safety
-/// This function reads from user-provided pointers and must validate them
-pub unsafe fn copy_from_user<T>(user_ptr: usize) -> Result<T, SyscallError>
-where
-    T: Copy,
-{
-    let size = core::mem::size_of::<T>();
-    validate_user_ptr(user_ptr as *const T, size)?;
-
-    // Use volatile read to prevent optimization issues
-    let value = ptr::read_volatile(user_ptr as *const T);
-    Ok(value)
-}
-
-/// Copy data from kernel space to user space
-///
-/// # Safety
-/// This function writes to user-provided pointers and must validate them
-pub unsafe fn copy_to_user<T>(user_ptr: usize, value: &T) -> Result<(), SyscallError>
-where
-    T: Copy,
-{
-    let size = core::mem::size_of::<T>();
-    validate_user_ptr(user_ptr as *const T, size)?;
-
-    // Use volatile write to prevent optimization issues
-    ptr::write_volatile(user_ptr as *mut T, *value);
-    Ok(())
-}
-
-/// Copy a byte slice from user space
-///
-/// # Safety
-/// This function reads from user-provided pointers and must validate them
-pub unsafe fn copy_slice_from_user(user_ptr: usize, len: usize) -> Result<Vec<u8>, SyscallError> {
-    validate_user_ptr(user_ptr as *const u8, len)?;
-
-    let slice = slice::from_raw_parts(user_ptr as *const u8, len);
-    Ok(slice.to_vec())
-}
-
-/// Copy a byte slice to user space
-///
-/// # Safety
-/// This function writes to user-provided pointers and must validate them
-pub unsafe fn copy_slice_to_user(user_ptr: usize, data: &[u8]) -> Result<(), SyscallError> {
-    validate_user_ptr(user_ptr as *const u8, data.len())?;
-
-    let dest = slice::from_raw_parts_mut(user_ptr as *mut u8, data.len());
-    dest.copy_from_slice(data);
-    Ok(())
+pub fn copy_string_from_user(user_ptr: usize) -> Result<String, SyscallError> {
+    strncpy_from_user(user_ptr, MAX_USER_STRING_LEN)
 }
 
 /// Copy a null-terminated string array from user space (like argv/envp)
-///
-/// # Safety
-/// This function reads from user-provided pointers and must validate them
-pub unsafe fn copy_string_array_from_user(array_ptr: usize) -> Result<Vec<String>, SyscallError> {
+pub fn copy_string_array_from_user(array_ptr: usize) -> Result<Vec<String>, SyscallError> {
     let mut cumulative = 0usize;
     copy_string_array_from_user_tracked(array_ptr, &mut cumulative)
 }
@@ -188,10 +47,7 @@ pub unsafe fn copy_string_array_from_user(array_ptr: usize) -> Result<Vec<String
 ///
 /// The array element count is also capped at MAX_ARGS (32768) to prevent
 /// DoS from massive counts of tiny strings.
-///
-/// # Safety
-/// This function reads from user-provided pointers and must validate them
-pub unsafe fn copy_string_array_from_user_tracked(
+pub fn copy_string_array_from_user_tracked(
     array_ptr: usize,
     cumulative_bytes: &mut usize,
 ) -> Result<Vec<String>, SyscallError> {
@@ -204,8 +60,7 @@ pub unsafe fn copy_string_array_from_user_tracked(
 
     // Read pointers until we hit null
     loop {
-        validate_user_ptr(current_ptr as *const usize, 8)?; // 64-bit pointer
-        let string_ptr = ptr::read_volatile(current_ptr as *const usize);
+        let string_ptr: usize = copy_from_user(current_ptr)?;
 
         if string_ptr == 0 {
             break;
@@ -230,15 +85,10 @@ pub unsafe fn copy_string_array_from_user_tracked(
 
         strings.push(string);
 
-        current_ptr += 8; // Move to next pointer
+        current_ptr = current_ptr
+            .checked_add(core::mem::size_of::<usize>())
+            .ok_or(SyscallError::InvalidPointer)?;
     }
 
     Ok(strings)
 }
-
-use core::ptr;
-
-#[cfg(feature = "alloc")]
-extern crate alloc;
-#[cfg(feature = "alloc")]
-use alloc::{string::String, vec::Vec};
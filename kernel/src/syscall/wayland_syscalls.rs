@@ -3,7 +3,10 @@
 //! Syscalls 240-247: Wayland client connection, surface management, and
 //! event delivery.
 
-use super::{SyscallError, SyscallResult};
+use super::{
+    uaccess::{access_ok, copy_slice_from_user, Access},
+    SyscallError, SyscallResult,
+};
 
 /// Connect to the Wayland compositor.
 ///
@@ -30,12 +33,9 @@ pub(super) fn sys_wl_send_message(
     msg_ptr: usize,
     msg_len: usize,
 ) -> SyscallResult {
-    super::validate_user_buffer(msg_ptr, msg_len)?;
+    let msg_bytes = copy_slice_from_user::<u8>(msg_ptr, msg_len)?;
 
-    // SAFETY: msg_ptr validated.
-    let msg_bytes = unsafe { core::slice::from_raw_parts(msg_ptr as *const u8, msg_len) };
-
-    crate::desktop::wayland::handle_client_message(client_id as u32, msg_bytes)
+    crate::desktop::wayland::handle_client_message(client_id as u32, &msg_bytes)
         .map_err(|_| SyscallError::InvalidArgument)?;
 
     Ok(0)
@@ -55,7 +55,7 @@ pub(super) fn sys_wl_recv_message(
     buf_ptr: usize,
     buf_len: usize,
 ) -> SyscallResult {
-    super::validate_user_buffer(buf_ptr, buf_len)?;
+    access_ok(buf_ptr, buf_len, Access::Write)?;
 
     let bytes_written =
         crate::desktop::wayland::read_client_events(client_id as u32, buf_ptr, buf_len)
@@ -144,7 +144,7 @@ pub(super) fn sys_wl_get_events(
     let byte_size = max_count
         .checked_mul(core::mem::size_of::<InputEvent>())
         .ok_or(SyscallError::InvalidArgument)?;
-    super::validate_user_buffer(events_ptr, byte_size)?;
+    access_ok(events_ptr, byte_size, Access::Write)?;
 
     let count = crate::desktop::wayland::get_client_events(client_id as u32, events_ptr, max_count)
         .map_err(|_| SyscallError::InvalidState)?;
@@ -9,6 +9,14 @@
 //! - `/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors` -- read
 //! - `/sys/devices/system/cpu/cpu0/cpufreq/scaling_min_freq` -- read
 //! - `/sys/devices/system/cpu/cpu0/cpufreq/scaling_max_freq` -- read
+//! - `/sys/class/power_supply/AC/online` -- read 1 on mains power
+//! - `/sys/class/power_supply/BAT0/{present,status,capacity}` -- read
+//! - `/sys/class/power_supply/BAT0/{energy_now,energy_full,power_now}` -- read
+//!   in uWh / uW
+//! - `/sys/class/thermal/thermal_zone0/{type,temp}` -- read, temp in
+//!   millidegrees Celsius
+//! - `/sys/class/thermal/thermal_zone0/trip_point_{0,1}_{type,temp}` -- read
+//!   passive and critical trips
 //!
 //! These paths are compatible with Linux sysfs conventions so that
 //! PowerDevil and other desktop tools can interact with the kernel.
@@ -62,16 +70,7 @@ fn power_state_write(value: &str) -> KernelResult<()> {
     match trimmed {
         "mem" => {
             println!("[SYSFS] Triggering S3 suspend via /sys/power/state");
-            #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-            {
-                crate::arch::x86_64::acpi_pm::acpi_suspend_s3()
-            }
-            #[cfg(not(all(target_arch = "x86_64", target_os = "none")))]
-            {
-                Err(KernelError::OperationNotSupported {
-                    operation: "S3 suspend (not x86_64)",
-                })
-            }
+            crate::power::system::suspend()
         }
         "disk" => {
             println!("[SYSFS] Triggering S4 hibernate via /sys/power/state");
@@ -134,87 +133,139 @@ fn backlight_max_brightness_read() -> String {
 
 /// Read handler for scaling_governor.
 fn cpufreq_governor_read() -> String {
-    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-    {
-        if crate::arch::x86_64::cpufreq::is_initialized() {
-            return String::from(crate::arch::x86_64::cpufreq::cpufreq_get_governor().name());
-        }
-    }
-    String::from("performance")
+    String::from(crate::power::get_governor().name())
 }
 
 /// Write handler for scaling_governor.
 fn cpufreq_governor_write(value: &str) -> KernelResult<()> {
-    let trimmed = value.trim();
-
-    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-    {
-        use crate::arch::x86_64::cpufreq::CpuGovernor;
-        let governor = CpuGovernor::from_name(trimmed).ok_or(KernelError::InvalidArgument {
+    let governor =
+        crate::power::Governor::from_name(value.trim()).ok_or(KernelError::InvalidArgument {
             name: "governor",
             value: "expected 'performance', 'powersave', or 'ondemand'",
         })?;
-        crate::arch::x86_64::cpufreq::cpufreq_set_governor(governor)
-    }
-
-    #[cfg(not(all(target_arch = "x86_64", target_os = "none")))]
-    {
-        let _ = trimmed;
-        Err(KernelError::OperationNotSupported {
-            operation: "cpufreq governor (not x86_64)",
-        })
-    }
+    crate::power::set_governor(governor)
 }
 
 /// Read handler for scaling_cur_freq.
 fn cpufreq_cur_freq_read() -> String {
-    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-    {
-        if crate::arch::x86_64::cpufreq::is_initialized() {
-            return format!("{}", crate::arch::x86_64::cpufreq::cpufreq_get_frequency());
-        }
-    }
-    String::from("0")
+    format!("{}", crate::power::current_frequency_khz())
 }
 
 /// Read handler for scaling_available_governors.
 fn cpufreq_available_governors_read() -> String {
-    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-    {
-        String::from(crate::arch::x86_64::cpufreq::cpufreq_available_governors())
-    }
-    #[cfg(not(all(target_arch = "x86_64", target_os = "none")))]
-    {
-        String::from("performance powersave ondemand")
-    }
+    String::from(crate::power::Governor::AVAILABLE)
 }
 
 /// Read handler for scaling_min_freq.
 fn cpufreq_min_freq_read() -> String {
-    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-    {
-        if crate::arch::x86_64::cpufreq::is_initialized() {
-            return format!(
-                "{}",
-                crate::arch::x86_64::cpufreq::cpufreq_get_min_frequency()
-            );
-        }
-    }
-    String::from("0")
+    format!("{}", crate::power::frequency_range_khz().0)
 }
 
 /// Read handler for scaling_max_freq.
 fn cpufreq_max_freq_read() -> String {
-    #[cfg(all(target_arch = "x86_64", target_os = "none"))]
-    {
-        if crate::arch::x86_64::cpufreq::is_initialized() {
-            return format!(
-                "{}",
-                crate::arch::x86_64::cpufreq::cpufreq_get_max_frequency()
-            );
-        }
-    }
-    String::from("0")
+    format!("{}", crate::power::frequency_range_khz().1)
+}
+
+// ---------------------------------------------------------------------------
+// /sys/class/power_supply/*
+// ---------------------------------------------------------------------------
+
+/// Read handler for AC/online.
+fn ac_online_read() -> String {
+    String::from(if crate::power::battery::status().on_ac() {
+        "1"
+    } else {
+        "0"
+    })
+}
+
+/// Read handler for BAT0/present.
+fn battery_present_read() -> String {
+    String::from(if crate::power::battery::status().battery_present {
+        "1"
+    } else {
+        "0"
+    })
+}
+
+/// Read handler for BAT0/status.
+fn battery_status_read() -> String {
+    String::from(crate::power::battery::status().state.name())
+}
+
+/// Read handler for BAT0/capacity.
+fn battery_capacity_read() -> String {
+    format!("{}", crate::power::battery::status().capacity_percent)
+}
+
+/// Read handler for BAT0/energy_now.
+fn battery_energy_now_read() -> String {
+    format!(
+        "{}",
+        crate::power::battery::status().energy_now_mwh as u64 * 1000
+    )
+}
+
+/// Read handler for BAT0/energy_full.
+fn battery_energy_full_read() -> String {
+    format!(
+        "{}",
+        crate::power::battery::status().energy_full_mwh as u64 * 1000
+    )
+}
+
+/// Read handler for BAT0/power_now.
+fn battery_power_now_read() -> String {
+    format!(
+        "{}",
+        crate::power::battery::status().power_now_mw as u64 * 1000
+    )
+}
+
+// ---------------------------------------------------------------------------
+// /sys/class/thermal/thermal_zone0/*
+// ---------------------------------------------------------------------------
+
+/// The first thermal zone, which the thermal_zone0 nodes describe.
+fn thermal_zone0() -> Option<crate::power::thermal::ZoneStatus> {
+    crate::power::thermal::zones().into_iter().next()
+}
+
+/// Format an optional temperature; empty when there is none.
+fn format_temp(temp_mc: Option<i32>) -> String {
+    temp_mc.map(|t| format!("{}", t)).unwrap_or_default()
+}
+
+/// Read handler for thermal_zone0/type.
+fn thermal_type_read() -> String {
+    thermal_zone0()
+        .map(|zone| String::from(zone.zone_type))
+        .unwrap_or_default()
+}
+
+/// Read handler for thermal_zone0/temp.
+fn thermal_temp_read() -> String {
+    format_temp(thermal_zone0().and_then(|zone| zone.temperature_mc))
+}
+
+/// Read handler for thermal_zone0/trip_point_0_type.
+fn thermal_trip0_type_read() -> String {
+    String::from("passive")
+}
+
+/// Read handler for thermal_zone0/trip_point_0_temp.
+fn thermal_trip0_temp_read() -> String {
+    format_temp(thermal_zone0().and_then(|zone| zone.trips.passive_mc))
+}
+
+/// Read handler for thermal_zone0/trip_point_1_type.
+fn thermal_trip1_type_read() -> String {
+    String::from("critical")
+}
+
+/// Read handler for thermal_zone0/trip_point_1_temp.
+fn thermal_trip1_temp_read() -> String {
+    format_temp(thermal_zone0().and_then(|zone| zone.trips.critical_mc))
 }
 
 // ---------------------------------------------------------------------------
@@ -316,6 +367,97 @@ pub(crate) fn sysfs_power_init() -> KernelResult<()> {
         cpufreq_max_freq_read,
     ))?;
 
+    // /sys/class/power_supply/AC/online
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/AC/online",
+        "Whether the machine runs on mains power",
+        ac_online_read,
+    ))?;
+
+    // /sys/class/power_supply/BAT0/present
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/BAT0/present",
+        "Whether a battery is present",
+        battery_present_read,
+    ))?;
+
+    // /sys/class/power_supply/BAT0/status
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/BAT0/status",
+        "Battery charging state",
+        battery_status_read,
+    ))?;
+
+    // /sys/class/power_supply/BAT0/capacity
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/BAT0/capacity",
+        "Battery charge in percent",
+        battery_capacity_read,
+    ))?;
+
+    // /sys/class/power_supply/BAT0/energy_now
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/BAT0/energy_now",
+        "Remaining battery energy in uWh",
+        battery_energy_now_read,
+    ))?;
+
+    // /sys/class/power_supply/BAT0/energy_full
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/BAT0/energy_full",
+        "Battery energy when full in uWh",
+        battery_energy_full_read,
+    ))?;
+
+    // /sys/class/power_supply/BAT0/power_now
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/power_supply/BAT0/power_now",
+        "Battery charge or discharge rate in uW",
+        battery_power_now_read,
+    ))?;
+
+    // /sys/class/thermal/thermal_zone0/type
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/thermal/thermal_zone0/type",
+        "Thermal zone type",
+        thermal_type_read,
+    ))?;
+
+    // /sys/class/thermal/thermal_zone0/temp
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/thermal/thermal_zone0/temp",
+        "Temperature in millidegrees Celsius",
+        thermal_temp_read,
+    ))?;
+
+    // /sys/class/thermal/thermal_zone0/trip_point_0_type
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/thermal/thermal_zone0/trip_point_0_type",
+        "Type of trip point 0",
+        thermal_trip0_type_read,
+    ))?;
+
+    // /sys/class/thermal/thermal_zone0/trip_point_0_temp
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/thermal/thermal_zone0/trip_point_0_temp",
+        "Passive trip in millidegrees Celsius",
+        thermal_trip0_temp_read,
+    ))?;
+
+    // /sys/class/thermal/thermal_zone0/trip_point_1_type
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/thermal/thermal_zone0/trip_point_1_type",
+        "Type of trip point 1",
+        thermal_trip1_type_read,
+    ))?;
+
+    // /sys/class/thermal/thermal_zone0/trip_point_1_temp
+    super::register_node(SysfsNode::read_only(
+        "/sys/class/thermal/thermal_zone0/trip_point_1_temp",
+        "Critical trip in millidegrees Celsius",
+        thermal_trip1_temp_read,
+    ))?;
+
     // DPMS idle timeout
     super::register_node(SysfsNode::read_write(
         "/sys/class/drm/card0/dpms_idle_timeout",
@@ -398,6 +540,20 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_power_supply_without_supplies() {
+        assert_eq!(ac_online_read(), "1");
+        assert_eq!(battery_present_read(), "0");
+        assert_eq!(battery_status_read(), "Unknown");
+    }
+
+    #[test]
+    fn test_format_temp() {
+        assert_eq!(format_temp(Some(45_000)), "45000");
+        assert_eq!(format_temp(Some(-5_000)), "-5000");
+        assert_eq!(format_temp(None), "");
+    }
+
     #[test]
     fn test_dpms_idle_timeout_read() {
         let val = dpms_idle_timeout_read();
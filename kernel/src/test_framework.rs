@@ -270,6 +270,15 @@ impl BenchmarkRunner {
         }
     }
 
+    /// Runner doing `warmup_iterations` untimed calls, then `iterations`
+    /// timed ones.
+    pub const fn with_iterations(iterations: u64, warmup_iterations: u64) -> Self {
+        Self {
+            iterations: if iterations == 0 { 1 } else { iterations },
+            warmup_iterations,
+        }
+    }
+
     pub fn run_benchmark<F>(&self, name: &'static str, mut f: F) -> BenchmarkResult
     where
         F: FnMut(),
@@ -310,6 +319,42 @@ impl BenchmarkRunner {
         serial_println!("[ok] avg: {} ns", result.avg_time_ns);
         result
     }
+
+    /// Like [`run_benchmark`](Self::run_benchmark), but keeps every sample
+    /// so the caller gets latency percentiles rather than just min/avg/max.
+    /// Prints nothing; reporting is left to the caller.
+    #[cfg(feature = "alloc")]
+    pub fn run_sampled<F>(&self, f: F) -> crate::bench::LatencySummary
+    where
+        F: FnMut(),
+    {
+        self.run_sampled_with(|| {}, f)
+    }
+
+    /// [`run_sampled`](Self::run_sampled) with an untimed `setup` call
+    /// before every iteration.
+    #[cfg(feature = "alloc")]
+    pub fn run_sampled_with<S, F>(&self, mut setup: S, mut f: F) -> crate::bench::LatencySummary
+    where
+        S: FnMut(),
+        F: FnMut(),
+    {
+        for _ in 0..self.warmup_iterations {
+            setup();
+            f();
+        }
+
+        let mut samples = Vec::with_capacity(self.iterations as usize);
+        for _ in 0..self.iterations {
+            setup();
+            let start = read_timestamp();
+            f();
+            let end = read_timestamp();
+            samples.push(cycles_to_ns(end.saturating_sub(start)));
+        }
+
+        crate::bench::LatencySummary::from_samples(&mut samples)
+    }
 }
 
 /// Macro for creating benchmarks
@@ -419,7 +464,7 @@ macro_rules! register_test {
 /// Creates a PackageManager, registers a test package in the resolver,
 /// installs it (with signature verification disabled), verifies it appears
 /// in the installed list, removes it, and verifies it is gone.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_package_install_remove() -> Result<(), KernelError> {
     use alloc::string::String;
 
@@ -496,7 +541,7 @@ pub fn test_package_install_remove() -> Result<(), KernelError> {
 ///
 /// Creates a DependencyResolver with packages that have transitive
 /// dependencies, resolves them, and verifies the correct topological order.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_package_dependency_resolution() -> Result<(), KernelError> {
     use alloc::string::String;
 
@@ -569,7 +614,7 @@ pub fn test_package_dependency_resolution() -> Result<(), KernelError> {
 ///
 /// Begins a transaction, simulates package state changes, rolls back,
 /// and verifies the original state is restored.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_package_transaction_rollback() -> Result<(), KernelError> {
     use crate::pkg::PackageManager;
 
@@ -610,7 +655,7 @@ pub fn test_package_transaction_rollback() -> Result<(), KernelError> {
 ///
 /// Parses sample TOML content and verifies key-value pairs are correctly
 /// extracted for strings, integers, booleans, and sections.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_toml_parsing() -> Result<(), KernelError> {
     use crate::pkg::toml_parser::{parse_toml, TomlValue};
 
@@ -679,7 +724,7 @@ name = \"test-package\"\nversion = \"1.2.3\"\nenabled = true\ncount = 42\n\n[bui
 ///
 /// Registers multiple packages in the resolver, searches by query, and
 /// verifies matching results are returned.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_package_search() -> Result<(), KernelError> {
     use alloc::string::String;
 
@@ -740,7 +785,7 @@ pub fn test_package_search() -> Result<(), KernelError> {
 ///
 /// Verifies that Version implements correct ordering for semantic versioning:
 /// 1.0.0 < 1.1.0 < 1.1.1 < 2.0.0, etc.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_version_comparison() -> Result<(), KernelError> {
     use crate::pkg::Version;
 
@@ -802,7 +847,7 @@ pub fn test_version_comparison() -> Result<(), KernelError> {
 /// Computes a binary delta between two similar byte slices, applies the delta
 /// to the original, and verifies the result matches the new data. Also checks
 /// that the delta contains non-empty operations.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_pkg_delta_compute_apply() -> Result<(), KernelError> {
     use crate::pkg::delta::{apply_delta, compute_delta};
 
@@ -835,7 +880,7 @@ pub fn test_pkg_delta_compute_apply() -> Result<(), KernelError> {
 /// Creates two build manifests with identical outputs but different build
 /// durations, and verifies that `verify_reproducible` considers them
 /// reproducible (since only output hashes matter, not wall-clock time).
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_pkg_reproducible_manifest() -> Result<(), KernelError> {
     use alloc::string::String;
 
@@ -885,7 +930,7 @@ pub fn test_pkg_reproducible_manifest() -> Result<(), KernelError> {
 /// Verifies that `detect_license` correctly identifies MIT and GPL licenses
 /// from representative text snippets, and that `LicenseCompatibility` reports
 /// MIT and Apache-2.0 as compatible.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_pkg_license_detection() -> Result<(), KernelError> {
     use crate::pkg::compliance::{detect_license, License, LicenseCompatibility};
 
@@ -928,7 +973,7 @@ pub fn test_pkg_license_detection() -> Result<(), KernelError> {
 /// Creates a `PackageSecurityScanner`, scans suspicious file paths (including
 /// `/etc/shadow` and `/dev/mem`), and verifies that high-severity findings are
 /// produced. Also scans excessive capabilities and checks for findings.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_pkg_security_scan() -> Result<(), KernelError> {
     use crate::pkg::testing::{PackageSecurityScanner, ScanSeverity};
 
@@ -969,7 +1014,7 @@ pub fn test_pkg_security_scan() -> Result<(), KernelError> {
 /// Verifies that the base system, essential apps, and driver package
 /// definitions are non-empty and well-formed: each set has a name and
 /// at least one package.
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "pkg"))]
 pub fn test_pkg_ecosystem_definitions() -> Result<(), KernelError> {
     use crate::pkg::ecosystem::{
         get_base_system_packages, get_driver_packages, get_essential_apps,
@@ -100,6 +100,7 @@ pub mod test_programs {
 
         pub fn run() -> Result<(), String> {
             crate::println!("Network test: checking network subsystem...");
+            #[cfg(feature = "net")]
             let _initialized = crate::drivers::network::is_network_initialized();
             crate::println!("  Network subsystem checked (non-critical)");
             Ok(())
@@ -421,7 +421,8 @@ fn build_minimal_elf(code: &[u8], load_addr: u64) -> Vec<u8> {
 ///
 /// Creates `/sbin/init` and `/bin/vsh` in the VFS so that
 /// `load_init_process()` and `load_shell()` can find real ELF executables
-/// instead of falling back to stub processes.
+/// instead of falling back to stub processes. Binaries supplied by a boot
+/// initramfs are left in place.
 ///
 /// Must be called after VFS is initialized (Stage 4) but before
 /// `create_init_process()` (Stage 6).
@@ -448,14 +449,23 @@ pub fn populate_initramfs() -> Result<(), crate::error::KernelError> {
     drop(vfs);
 
     // Write init binary to /sbin/init
-    fs::write_file("/sbin/init", &init_elf)?;
+    write_if_missing("/sbin/init", &init_elf)?;
 
     // Write shell binary to /bin/vsh
-    fs::write_file("/bin/vsh", &shell_elf)?;
+    write_if_missing("/bin/vsh", &shell_elf)?;
 
     // Write hello test program to /bin/hello (x86_64 only for now)
     #[cfg(target_arch = "x86_64")]
-    fs::write_file("/bin/hello", &hello_elf)?;
+    write_if_missing("/bin/hello", &hello_elf)?;
 
     Ok(())
 }
+
+/// Write an embedded binary unless the boot initramfs already provided one.
+#[cfg(feature = "alloc")]
+fn write_if_missing(path: &str, data: &[u8]) -> Result<(), crate::error::KernelError> {
+    if crate::fs::file_exists(path) {
+        return Ok(());
+    }
+    crate::fs::write_file(path, data).map(|_| ())
+}
@@ -20,9 +20,31 @@ use crate::{
     process::{lifecycle, ProcessId},
 };
 
+#[cfg(feature = "alloc")]
+crate::kernel_param! {
+    /// Program to run as init instead of searching the usual locations.
+    pub static INIT: String = String::new(), "init";
+}
+
 /// Load and execute the init process
 #[cfg(feature = "alloc")]
 pub fn load_init_process() -> Result<ProcessId, KernelError> {
+    // An explicit init= wins; fall back to the search below if it fails
+    // init starts with the kernel's default environment
+    let envp = crate::stdlib::system::default_envp();
+    let envp: Vec<&str> = envp.iter().map(String::as_str).collect();
+
+    let init = INIT.get();
+    if !init.is_empty() {
+        match load_user_program(&init, &[&init], &envp) {
+            Ok(pid) => {
+                println!("[LOADER] init from {} (PID {})", init, pid.0);
+                return Ok(pid);
+            }
+            Err(_e) => println!("[LOADER] init={} failed: {:?}", init, _e),
+        }
+    }
+
     // Try to load init from various locations
     let init_paths = [
         "/sbin/init",
@@ -33,7 +55,7 @@ pub fn load_init_process() -> Result<ProcessId, KernelError> {
     ];
 
     for path in &init_paths {
-        match load_user_program(path, &[], &[]) {
+        match load_user_program(path, &[path], &envp) {
             Ok(pid) => {
                 println!("[LOADER] init from {} (PID {})", path, pid.0);
                 return Ok(pid);
@@ -94,6 +116,8 @@ pub fn load_user_program(
     // Get the entry point
     let entry_point = binary.entry_point as usize;
 
+    let profile = crate::security::sandbox::profile_for(path)?;
+
     // Extract program name from path
     let name: String = path.rsplit('/').next().unwrap_or("unknown").into();
 
@@ -114,6 +138,9 @@ pub fn load_user_program(
     };
 
     let pid = lifecycle::create_process_with_options(options)?;
+    if let Some(process) = crate::process::get_process(pid) {
+        crate::security::sandbox::install(process, profile);
+    }
 
     #[cfg(target_arch = "x86_64")]
     // SAFETY: raw_serial_str writes to the COM1 I/O port for diagnostic output.
@@ -185,6 +212,7 @@ pub fn load_user_program(
 
         // Use the ELF loader to load the binary into the process's address space
         let entry = ElfLoader::load(&buffer, &mut *memory_space)?;
+        memory_space.set_backing(binary.load_base, binary.load_size, path);
 
         // Verify the entry point matches
         if entry != binary.entry_point {
@@ -194,6 +222,12 @@ pub fn load_user_program(
             });
         }
 
+        process
+            .wx_exempt
+            .store(binary.wx_needed, core::sync::atomic::Ordering::Release);
+        *process.symbols.lock() =
+            crate::elf::symbols::SymbolMap::from_elf(&buffer, 0).map(alloc::sync::Arc::new);
+
         // Handle dynamic linking if needed
         if binary.dynamic {
             if let Some(interpreter) = &binary.interpreter {
@@ -340,6 +374,11 @@ fn load_dynamic_linker(
             }
         }
     }
+    memory_space.set_backing(
+        interp_base + interp_binary.load_base,
+        interp_binary.load_size,
+        interpreter_path,
+    );
 
     // Calculate interpreter entry point (adjusted for base address)
     let interp_entry = interp_base + interp_binary.entry_point;
@@ -0,0 +1,450 @@
+//! Kernel command line
+//!
+//! Holds the boot command line as a whitespace-separated list of `key=value`
+//! (or bare `flag`) parameters. The line comes from, in order of preference:
+//!
+//! 1. the bootloader: `/chosen/bootargs` of the device tree on AArch64 and
+//!    RISC-V, installed by the boot entry through
+//!    [`fdt::init`](super::fdt::init);
+//! 2. the `.cmdline` section of the kernel image, which `bootimage-builder
+//!    --cmdline` patches (the x86_64 boot protocol has no command line of its
+//!    own);
+//! 3. the line baked in at build time through the `VERIDIAN_CMDLINE`
+//!    environment variable, which is what an unpatched `.cmdline` holds.
+//!
+//! The active line is exposed as `/proc/cmdline`.
+//!
+//! Subsystems declare typed parameters with
+//! [`kernel_param!`](crate::kernel_param). A parameter is parsed from the line
+//! the first time it is read and then registered, so that root can list and
+//! change it at runtime through `sysctl kernel.param.<name>` (see [`params`]
+//! and [`tune`]).
+
+use alloc::{format, string::String, vec::Vec};
+
+use spin::Mutex;
+
+use crate::error::KernelError;
+
+/// Longest command line kept, in bytes (including the embedded one).
+pub const CMDLINE_SIZE: usize = 2048;
+
+/// Magic at the start of the `.cmdline` section.
+pub const CMDLINE_MAGIC: &[u8; 4] = b"VCMD";
+
+/// `.cmdline` layout: magic, `u32` length (little-endian), text.
+const CMDLINE_HEADER_LEN: usize = 8;
+
+/// Command line provided at build time (`VERIDIAN_CMDLINE=...`).
+const BUILTIN_CMDLINE: &str = match option_env!("VERIDIAN_CMDLINE") {
+    Some(line) => line,
+    None => "",
+};
+
+const fn embedded_default() -> [u8; CMDLINE_HEADER_LEN + CMDLINE_SIZE] {
+    let mut area = [0u8; CMDLINE_HEADER_LEN + CMDLINE_SIZE];
+    let text = BUILTIN_CMDLINE.as_bytes();
+    assert!(text.len() <= CMDLINE_SIZE, "VERIDIAN_CMDLINE is too long");
+    let mut i = 0;
+    while i < 4 {
+        area[i] = CMDLINE_MAGIC[i];
+        i += 1;
+    }
+    let len = (text.len() as u32).to_le_bytes();
+    let mut i = 0;
+    while i < 4 {
+        area[4 + i] = len[i];
+        i += 1;
+    }
+    let mut i = 0;
+    while i < text.len() {
+        area[CMDLINE_HEADER_LEN + i] = text[i];
+        i += 1;
+    }
+    area
+}
+
+/// The command line carried in the kernel image, patched in place by
+/// `bootimage-builder --cmdline` and found by section name.
+#[no_mangle]
+#[used]
+#[cfg_attr(target_os = "none", link_section = ".cmdline")]
+pub static VERIDIAN_EMBEDDED_CMDLINE: [u8; CMDLINE_HEADER_LEN + CMDLINE_SIZE] = embedded_default();
+
+/// Command line installed by the boot entry. Fixed-size so that it can be
+/// set before the heap exists.
+struct BootCmdline {
+    text: [u8; CMDLINE_SIZE],
+    len: usize,
+    present: bool,
+}
+
+static CMDLINE: Mutex<BootCmdline> = Mutex::new(BootCmdline {
+    text: [0; CMDLINE_SIZE],
+    len: 0,
+    present: false,
+});
+
+/// Longest prefix of `bytes` of at most `max` bytes that is valid UTF-8.
+fn utf8_prefix(bytes: &[u8], max: usize) -> &str {
+    let bytes = &bytes[..bytes.len().min(max)];
+    match core::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or(""),
+    }
+}
+
+/// Install the command line passed by the bootloader. Lines longer than
+/// [`CMDLINE_SIZE`] are cut off. Does not allocate.
+pub fn set(cmdline: &str) {
+    let text = utf8_prefix(cmdline.trim().as_bytes(), CMDLINE_SIZE);
+    let mut boot = CMDLINE.lock();
+    boot.text[..text.len()].copy_from_slice(text.as_bytes());
+    boot.len = text.len();
+    boot.present = true;
+}
+
+/// The command line embedded in the kernel image.
+fn embedded() -> &'static str {
+    // Read through black_box: the table is patched after linking, so its
+    // compile-time contents must not be folded into the reads.
+    let area: &'static [u8; CMDLINE_HEADER_LEN + CMDLINE_SIZE] =
+        core::hint::black_box(&VERIDIAN_EMBEDDED_CMDLINE);
+    if &area[..4] != CMDLINE_MAGIC {
+        return "";
+    }
+    let len = u32::from_le_bytes([area[4], area[5], area[6], area[7]]) as usize;
+    utf8_prefix(&area[CMDLINE_HEADER_LEN..], len).trim()
+}
+
+/// The active command line.
+pub fn get() -> String {
+    let boot = CMDLINE.lock();
+    if boot.present {
+        String::from(utf8_prefix(&boot.text, boot.len))
+    } else {
+        String::from(embedded())
+    }
+}
+
+/// Find the value of `key` in `cmdline`. Later occurrences override earlier
+/// ones; a bare `key` yields an empty value.
+pub fn find_param<'a>(cmdline: &'a str, key: &str) -> Option<&'a str> {
+    cmdline
+        .split_whitespace()
+        .rev()
+        .find_map(|token| match token.split_once('=') {
+            Some((k, v)) if k == key => Some(v),
+            None if token == key => Some(""),
+            _ => None,
+        })
+}
+
+/// Value of `key` on the active command line.
+pub fn param(key: &str) -> Option<String> {
+    find_param(&get(), key).map(String::from)
+}
+
+// ---------------------------------------------------------------------------
+// Typed parameters
+// ---------------------------------------------------------------------------
+
+/// A type that a command line parameter can hold.
+pub trait ParamValue: Clone + Send + Sync + 'static {
+    /// Parse the text after `key=` (empty for a bare `key`).
+    fn parse_param(text: &str) -> Option<Self>;
+
+    /// Text form, as shown by sysctl.
+    fn format_param(&self) -> String;
+}
+
+impl ParamValue for bool {
+    /// A bare flag is `true`, as are `1`/`y`/`yes`/`on`/`true`; `0`/`n`/
+    /// `no`/`off`/`false` are `false`.
+    fn parse_param(text: &str) -> Option<Self> {
+        match text {
+            "" | "1" | "y" | "yes" | "on" | "true" => Some(true),
+            "0" | "n" | "no" | "off" | "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn format_param(&self) -> String {
+        String::from(if *self { "1" } else { "0" })
+    }
+}
+
+macro_rules! integer_param {
+    ($($ty:ty),*) => {
+        $(
+            impl ParamValue for $ty {
+                fn parse_param(text: &str) -> Option<Self> {
+                    text.parse().ok()
+                }
+
+                fn format_param(&self) -> String {
+                    format!("{}", self)
+                }
+            }
+        )*
+    };
+}
+
+integer_param!(u32, u64, usize);
+
+impl ParamValue for String {
+    fn parse_param(text: &str) -> Option<Self> {
+        Some(String::from(text))
+    }
+
+    fn format_param(&self) -> String {
+        self.clone()
+    }
+}
+
+/// Registered-parameter view used by sysctl, independent of the value type.
+pub trait Tunable: Sync {
+    /// Name on the command line.
+    fn name(&self) -> &'static str;
+
+    /// One-line description.
+    fn help(&self) -> &'static str;
+
+    /// Current value as text.
+    fn show(&self) -> String;
+
+    /// Parse and apply `text`. Returns the value now in effect.
+    fn tune(&self, text: &str) -> Result<String, KernelError>;
+}
+
+struct ParamState<T> {
+    value: T,
+    /// Whether the value came from the command line or [`Param::set`]
+    /// rather than the default.
+    explicit: bool,
+}
+
+/// A typed command line parameter, declared with
+/// [`kernel_param!`](crate::kernel_param).
+pub struct Param<T: ParamValue> {
+    name: &'static str,
+    help: &'static str,
+    default: T,
+    on_set: Option<fn(&T)>,
+    /// `None` until the command line has been consulted.
+    state: Mutex<Option<ParamState<T>>>,
+}
+
+impl<T: ParamValue> Param<T> {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        default: T,
+        on_set: Option<fn(&T)>,
+    ) -> Self {
+        Self {
+            name,
+            help,
+            default,
+            on_set,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Run `f` on the state, parsing the command line first if needed.
+    fn with_state<R>(&'static self, f: impl FnOnce(&mut ParamState<T>) -> R) -> R {
+        let mut state = self.state.lock();
+        let state = match &mut *state {
+            Some(state) => state,
+            slot => {
+                let parsed = find_param(&get(), self.name).and_then(|text| {
+                    let value = T::parse_param(text);
+                    if value.is_none() {
+                        crate::klog!(Warn, "cmdline", "ignoring invalid {}={}", self.name, text);
+                    }
+                    value
+                });
+                register(self);
+                slot.insert(ParamState {
+                    explicit: parsed.is_some(),
+                    value: parsed.unwrap_or_else(|| self.default.clone()),
+                })
+            }
+        };
+        f(state)
+    }
+
+    /// Current value: from the command line, the last [`set`](Self::set),
+    /// or the default.
+    pub fn get(&'static self) -> T {
+        self.with_state(|state| state.value.clone())
+    }
+
+    /// Whether the value was given on the command line or set at runtime.
+    pub fn is_set(&'static self) -> bool {
+        self.with_state(|state| state.explicit)
+    }
+
+    /// Change the value at runtime.
+    pub fn set(&'static self, value: T) {
+        self.with_state(|state| {
+            state.value = value.clone();
+            state.explicit = true;
+        });
+        if let Some(on_set) = self.on_set {
+            on_set(&value);
+        }
+    }
+}
+
+impl<T: ParamValue> Tunable for Param<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn help(&self) -> &'static str {
+        self.help
+    }
+
+    fn show(&self) -> String {
+        // Only registered (and therefore resolved) parameters are shown.
+        match &*self.state.lock() {
+            Some(state) => state.value.format_param(),
+            None => self.default.format_param(),
+        }
+    }
+
+    fn tune(&self, text: &str) -> Result<String, KernelError> {
+        let value = T::parse_param(text.trim()).ok_or(KernelError::InvalidArgument {
+            name: "value",
+            value: "not valid for this parameter",
+        })?;
+        let shown = value.format_param();
+        {
+            let mut state = self.state.lock();
+            *state = Some(ParamState {
+                value: value.clone(),
+                explicit: true,
+            });
+        }
+        if let Some(on_set) = self.on_set {
+            on_set(&value);
+        }
+        Ok(shown)
+    }
+}
+
+/// Parameters read so far, in registration order.
+static REGISTRY: Mutex<Vec<&'static dyn Tunable>> = Mutex::new(Vec::new());
+
+fn register(param: &'static dyn Tunable) {
+    let mut registry = REGISTRY.lock();
+    if !registry.iter().any(|p| p.name() == param.name()) {
+        registry.push(param);
+    }
+}
+
+/// All registered parameters.
+pub fn params() -> Vec<&'static dyn Tunable> {
+    REGISTRY.lock().clone()
+}
+
+/// Change the registered parameter `name` at runtime (sysctl helper).
+pub fn tune(name: &str, value: &str) -> Result<String, KernelError> {
+    let param = REGISTRY
+        .lock()
+        .iter()
+        .copied()
+        .find(|p| p.name() == name)
+        .ok_or(KernelError::NotFound {
+            resource: "kernel parameter",
+            id: 0,
+        })?;
+    param.tune(value)
+}
+
+/// Declare a typed command line parameter.
+///
+/// ```ignore
+/// kernel_param! {
+///     /// Keep application processors offline.
+///     pub static NOSMP: bool = false, "nosmp";
+/// }
+/// ```
+///
+/// The doc comment doubles as the parameter's help text. An optional
+/// `on_set = <fn(&T)>` runs whenever the value is changed at runtime.
+#[macro_export]
+macro_rules! kernel_param {
+    (
+        $(#[doc = $doc:literal])*
+        $vis:vis static $ident:ident: $ty:ty = $default:expr, $name:literal
+        $(, on_set = $on_set:expr)? $(,)?;
+    ) => {
+        $(#[doc = $doc])*
+        $vis static $ident: $crate::utils::cmdline::Param<$ty> =
+            $crate::utils::cmdline::Param::new(
+                $name,
+                concat!($($doc),*),
+                $default,
+                $crate::kernel_param!(@on_set $($on_set)?),
+            );
+    };
+    (@on_set) => {
+        None
+    };
+    (@on_set $on_set:expr) => {
+        Some($on_set as fn(&_))
+    };
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_param() {
+        let line = "quiet max_procs=512 limits.max_files=64 max_procs=2048";
+        assert_eq!(find_param(line, "max_procs"), Some("2048"));
+        assert_eq!(find_param(line, "limits.max_files"), Some("64"));
+        assert_eq!(find_param(line, "quiet"), Some(""));
+        assert_eq!(find_param(line, "missing"), None);
+        assert_eq!(find_param("", "quiet"), None);
+    }
+
+    #[test]
+    fn test_param_values() {
+        assert_eq!(bool::parse_param(""), Some(true));
+        assert_eq!(bool::parse_param("off"), Some(false));
+        assert_eq!(bool::parse_param("maybe"), None);
+        assert_eq!(u32::parse_param("42"), Some(42));
+        assert_eq!(u32::parse_param("-1"), None);
+        assert_eq!(
+            String::parse_param("/sbin/init").as_deref(),
+            Some("/sbin/init")
+        );
+    }
+
+    #[test]
+    fn test_embedded_cmdline() {
+        assert_eq!(&VERIDIAN_EMBEDDED_CMDLINE[..4], CMDLINE_MAGIC);
+        assert_eq!(embedded(), BUILTIN_CMDLINE.trim());
+    }
+
+    crate::kernel_param! {
+        /// Test parameter.
+        static TEST_PARAM: u32 = 7, "test.param";
+    }
+
+    #[test]
+    fn test_param_tune() {
+        assert_eq!(TEST_PARAM.get(), 7);
+        assert!(!TEST_PARAM.is_set());
+        assert!(params().iter().any(|p| p.name() == "test.param"));
+        assert_eq!(tune("test.param", "9").unwrap(), "9");
+        assert_eq!(TEST_PARAM.get(), 9);
+        assert!(TEST_PARAM.is_set());
+        assert!(tune("test.param", "nine").is_err());
+        assert!(tune("test.missing", "1").is_err());
+    }
+}
@@ -0,0 +1,563 @@
+//! Flattened device tree reader
+//!
+//! Walks a devicetree blob in place: nodes with their `compatible` strings,
+//! `reg` ranges and `interrupts` cells, and the `/chosen` properties the
+//! command line comes from. Nothing is allocated, so this works in early
+//! boot.
+//!
+//! The boot entry hands the firmware's blob to [`init`], which keeps a copy
+//! (the original lives in RAM the kernel will reuse) for driver matching
+//! later through [`with_blob`].
+
+use spin::Mutex;
+
+/// Magic at the start of a devicetree blob (big-endian).
+pub const FDT_MAGIC: u32 = 0xd00d_feed;
+
+/// Size of the blob header.
+const HEADER_LEN: usize = 40;
+
+/// Refuse blobs larger than this; a real one is a few KiB.
+const MAX_SIZE: usize = 1 << 20;
+
+/// Largest blob [`init`] keeps a copy of.
+pub const SAVED_SIZE: usize = 64 * 1024;
+
+/// Deepest node nesting followed; deeper nodes are skipped.
+const MAX_DEPTH: usize = 16;
+
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+fn be32(blob: &[u8], off: usize) -> Option<u32> {
+    let bytes = blob.get(off..off + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// NUL-terminated string starting at `off`.
+fn c_str(blob: &[u8], off: usize) -> Option<&[u8]> {
+    let rest = blob.get(off..)?;
+    let len = rest.iter().position(|&b| b == 0)?;
+    Some(&rest[..len])
+}
+
+fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+/// The devicetree blob at `addr`, if one starts there.
+///
+/// # Safety
+///
+/// `addr` must be zero or readable for [`HEADER_LEN`] bytes, and for the
+/// header's `totalsize` if the magic matches.
+pub unsafe fn from_addr(addr: usize) -> Option<&'static [u8]> {
+    if addr == 0 || !addr.is_multiple_of(4) {
+        return None;
+    }
+    // SAFETY: the caller guarantees the header is readable.
+    let header = unsafe { core::slice::from_raw_parts(addr as *const u8, HEADER_LEN) };
+    if be32(header, 0)? != FDT_MAGIC {
+        return None;
+    }
+    let size = be32(header, 4)? as usize;
+    if !(HEADER_LEN..=MAX_SIZE).contains(&size) {
+        return None;
+    }
+    // SAFETY: the magic matched, so the caller guarantees `size` bytes.
+    Some(unsafe { core::slice::from_raw_parts(addr as *const u8, size) })
+}
+
+/// Bytes of the blob actually in use.
+///
+/// Firmware often pads `totalsize` (QEMU's Arm blob claims 1 MiB); only the
+/// header, structure block and strings block are needed.
+fn used_size(blob: &[u8]) -> Option<usize> {
+    let total = be32(blob, 4)? as usize;
+    let structs_end = (be32(blob, 8)? as usize).checked_add(be32(blob, 36)? as usize)?;
+    let strings_end = (be32(blob, 12)? as usize).checked_add(be32(blob, 32)? as usize)?;
+    let used = structs_end.max(strings_end);
+    (used <= total).then_some(used)
+}
+
+/// Copy of the boot blob, kept by [`init`].
+struct SavedBlob {
+    data: [u8; SAVED_SIZE],
+    len: usize,
+}
+
+static SAVED: Mutex<SavedBlob> = Mutex::new(SavedBlob {
+    data: [0; SAVED_SIZE],
+    len: 0,
+});
+
+/// Take the device tree the firmware passed at boot.
+///
+/// Keeps a copy for [`with_blob`] and installs `/chosen/bootargs` as the
+/// kernel command line. Returns whether a blob was found at `addr`.
+///
+/// # Safety
+///
+/// Same as [`from_addr`].
+pub unsafe fn init(addr: usize) -> bool {
+    // SAFETY: forwarded from the caller.
+    let Some(blob) = (unsafe { from_addr(addr) }) else {
+        return false;
+    };
+    if let Some(len) = used_size(blob).filter(|&len| len <= SAVED_SIZE) {
+        let mut saved = SAVED.lock();
+        saved.data[..len].copy_from_slice(&blob[..len]);
+        saved.len = len;
+    }
+    if let Some(bootargs) = bootargs(blob) {
+        super::cmdline::set(bootargs);
+    }
+    true
+}
+
+/// Run `f` on the device tree saved at boot, if there is one.
+pub fn with_blob<R>(f: impl FnOnce(Fdt<'_>) -> R) -> Option<R> {
+    let saved = SAVED.lock();
+    Fdt::new(&saved.data[..saved.len]).map(f)
+}
+
+/// A devicetree blob.
+#[derive(Clone, Copy)]
+pub struct Fdt<'a> {
+    blob: &'a [u8],
+    structs: usize,
+    strings: usize,
+}
+
+impl<'a> Fdt<'a> {
+    /// Wrap `blob` after checking its magic.
+    pub fn new(blob: &'a [u8]) -> Option<Self> {
+        if be32(blob, 0)? != FDT_MAGIC {
+            return None;
+        }
+        Some(Self {
+            blob,
+            structs: be32(blob, 8)? as usize,
+            strings: be32(blob, 12)? as usize,
+        })
+    }
+
+    /// All nodes in document order, starting with the root.
+    pub fn nodes(&self) -> Nodes<'a> {
+        Nodes {
+            fdt: *self,
+            off: self.structs,
+            depth: 0,
+            // The root's parent context: the spec defaults.
+            context: [Context {
+                address_cells: 2,
+                size_cells: 1,
+                interrupt_parent: None,
+            }; MAX_DEPTH + 1],
+            done: false,
+        }
+    }
+
+    /// Enabled nodes listing `compatible` among their compatible strings.
+    pub fn compatible_nodes<'c>(&self, compatible: &'c str) -> impl Iterator<Item = Node<'a>> + 'c
+    where
+        'a: 'c,
+    {
+        self.nodes()
+            .filter(move |node| node.is_enabled() && node.is_compatible(compatible))
+    }
+
+    /// The node whose `phandle` is `phandle`.
+    pub fn node_by_phandle(&self, phandle: u32) -> Option<Node<'a>> {
+        self.nodes().find(|node| node.phandle() == Some(phandle))
+    }
+
+    /// The `/chosen` node.
+    pub fn chosen(&self) -> Option<Node<'a>> {
+        self.nodes()
+            .find(|node| node.depth() == 1 && node.name() == "chosen")
+    }
+
+    fn string_at(&self, off: usize) -> Option<&'a [u8]> {
+        c_str(self.blob, self.strings.checked_add(off)?)
+    }
+}
+
+/// Addressing context a node inherits from its parent.
+#[derive(Clone, Copy)]
+struct Context {
+    address_cells: u32,
+    size_cells: u32,
+    interrupt_parent: Option<u32>,
+}
+
+/// Iterator over the nodes of a blob; see [`Fdt::nodes`].
+pub struct Nodes<'a> {
+    fdt: Fdt<'a>,
+    off: usize,
+    depth: usize,
+    /// `context[d]` applies to the children of the node at depth `d - 1`.
+    context: [Context; MAX_DEPTH + 1],
+    done: bool,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        while !self.done {
+            let Some(token) = be32(self.fdt.blob, self.off) else {
+                break;
+            };
+            self.off += 4;
+            match token {
+                FDT_BEGIN_NODE => {
+                    let Some(name) = c_str(self.fdt.blob, self.off) else {
+                        break;
+                    };
+                    self.off = align4(self.off + name.len() + 1);
+                    let depth = self.depth;
+                    self.depth += 1;
+                    if depth > MAX_DEPTH {
+                        continue;
+                    }
+                    let node = Node {
+                        fdt: self.fdt,
+                        name: core::str::from_utf8(name).unwrap_or(""),
+                        depth,
+                        props: self.off,
+                        parent: self.context[depth],
+                    };
+                    if depth < MAX_DEPTH {
+                        self.context[depth + 1] = Context {
+                            address_cells: node.u32_property("#address-cells").unwrap_or(2),
+                            size_cells: node.u32_property("#size-cells").unwrap_or(1),
+                            interrupt_parent: node.interrupt_parent(),
+                        };
+                    }
+                    return Some(node);
+                }
+                FDT_END_NODE => match self.depth.checked_sub(1) {
+                    Some(depth) => self.depth = depth,
+                    None => break,
+                },
+                FDT_PROP => {
+                    let Some(len) = be32(self.fdt.blob, self.off) else {
+                        break;
+                    };
+                    self.off = align4(self.off + 8 + len as usize);
+                }
+                FDT_NOP => {}
+                FDT_END => break,
+                _ => break,
+            }
+        }
+        self.done = true;
+        None
+    }
+}
+
+/// A node of the tree.
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    fdt: Fdt<'a>,
+    name: &'a str,
+    depth: usize,
+    /// Offset of the first token after the node's name.
+    props: usize,
+    parent: Context,
+}
+
+impl<'a> Node<'a> {
+    /// Node name including any unit address, e.g. `virtio_mmio@a000000`.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Nesting depth; the root is 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Raw value of property `name`.
+    pub fn property(&self, name: &str) -> Option<&'a [u8]> {
+        let blob = self.fdt.blob;
+        let mut off = self.props;
+        // Properties come before any child node.
+        loop {
+            match be32(blob, off)? {
+                FDT_PROP => {
+                    let len = be32(blob, off + 4)? as usize;
+                    let name_off = be32(blob, off + 8)? as usize;
+                    let value = blob.get(off + 12..off + 12 + len)?;
+                    if self.fdt.string_at(name_off)? == name.as_bytes() {
+                        return Some(value);
+                    }
+                    off = align4(off + 12 + len);
+                }
+                FDT_NOP => off += 4,
+                _ => return None,
+            }
+        }
+    }
+
+    /// Property `name` as a single big-endian cell.
+    pub fn u32_property(&self, name: &str) -> Option<u32> {
+        let value = self.property(name)?;
+        (value.len() == 4).then(|| be32(value, 0)).flatten()
+    }
+
+    /// Property `name` as a NUL-terminated string.
+    pub fn str_property(&self, name: &str) -> Option<&'a str> {
+        let value = self.property(name)?;
+        core::str::from_utf8(value.strip_suffix(&[0]).unwrap_or(value)).ok()
+    }
+
+    /// The node's `compatible` strings, most specific first.
+    pub fn compatible(&self) -> impl Iterator<Item = &'a str> {
+        self.property("compatible")
+            .unwrap_or(&[])
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| core::str::from_utf8(s).ok())
+    }
+
+    /// Whether `compatible` is among the node's compatible strings.
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible().any(|c| c == compatible)
+    }
+
+    /// Whether the node is usable: `status` absent, `"okay"` or `"ok"`.
+    pub fn is_enabled(&self) -> bool {
+        matches!(
+            self.str_property("status"),
+            None | Some("okay") | Some("ok")
+        )
+    }
+
+    /// The node's `phandle`.
+    pub fn phandle(&self) -> Option<u32> {
+        self.u32_property("phandle")
+            .or_else(|| self.u32_property("linux,phandle"))
+    }
+
+    /// The phandle of the node's interrupt controller, own or inherited.
+    pub fn interrupt_parent(&self) -> Option<u32> {
+        self.u32_property("interrupt-parent")
+            .or(self.parent.interrupt_parent)
+    }
+
+    /// `(address, size)` pairs of the `reg` property, in the parent's
+    /// address space.
+    pub fn reg(&self) -> impl Iterator<Item = (u64, u64)> + 'a {
+        let address_cells = self.parent.address_cells as usize;
+        let size_cells = self.parent.size_cells as usize;
+        let stride = (address_cells + size_cells) * 4;
+        let value = match stride {
+            0 => &[][..],
+            _ => self.property("reg").unwrap_or(&[]),
+        };
+        value.chunks_exact(stride.max(1)).map(move |entry| {
+            let (address, size) = entry.split_at(address_cells * 4);
+            (cells_to_u64(address), cells_to_u64(size))
+        })
+    }
+
+    /// Raw cells of the `interrupts` property; their meaning depends on
+    /// the interrupt controller (see [`Node::interrupt_parent`]).
+    pub fn interrupts(&self) -> impl Iterator<Item = u32> + 'a {
+        self.property("interrupts")
+            .unwrap_or(&[])
+            .chunks_exact(4)
+            .map(|cell| u32::from_be_bytes([cell[0], cell[1], cell[2], cell[3]]))
+    }
+}
+
+/// Big-endian cells as a number, keeping the low 64 bits.
+fn cells_to_u64(cells: &[u8]) -> u64 {
+    cells.chunks_exact(4).fold(0, |acc, cell| {
+        (acc << 32) | u32::from_be_bytes([cell[0], cell[1], cell[2], cell[3]]) as u64
+    })
+}
+
+/// Value of property `name` of the `/chosen` node.
+pub fn chosen_property<'a>(blob: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    Fdt::new(blob)?.chosen()?.property(name)
+}
+
+/// The `/chosen/bootargs` string.
+pub fn bootargs(blob: &[u8]) -> Option<&str> {
+    Fdt::new(blob)?.chosen()?.str_property("bootargs")
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn push32(out: &mut Vec<u8>, value: u32) {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn begin_node(out: &mut Vec<u8>, name: &str) {
+        push32(out, FDT_BEGIN_NODE);
+        out.extend_from_slice(name.as_bytes());
+        out.push(0);
+        out.resize(align4(out.len()), 0);
+    }
+
+    fn prop(out: &mut Vec<u8>, name_off: u32, value: &[u8]) {
+        push32(out, FDT_PROP);
+        push32(out, value.len() as u32);
+        push32(out, name_off);
+        out.extend_from_slice(value);
+        out.resize(align4(out.len()), 0);
+    }
+
+    fn wrap(structs: &[u8], strings: &[u8]) -> Vec<u8> {
+        let structs_off = HEADER_LEN;
+        let strings_off = structs_off + structs.len();
+        let total = strings_off + strings.len();
+        let mut blob = Vec::new();
+        for value in [
+            FDT_MAGIC,
+            total as u32,
+            structs_off as u32,
+            strings_off as u32,
+            0,
+            17,
+            16,
+            0,
+            strings.len() as u32,
+            structs.len() as u32,
+        ] {
+            push32(&mut blob, value);
+        }
+        blob.extend_from_slice(structs);
+        blob.extend_from_slice(strings);
+        blob
+    }
+
+    fn cells(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    /// `/ { model; cpus { bootargs; }; chosen { stdout-path; bootargs; }; }`
+    fn sample() -> Vec<u8> {
+        let strings = b"model\0bootargs\0stdout-path\0";
+        let mut structs = Vec::new();
+        begin_node(&mut structs, "");
+        prop(&mut structs, 0, b"qemu\0");
+        begin_node(&mut structs, "cpus");
+        prop(&mut structs, 6, b"wrong\0");
+        push32(&mut structs, FDT_END_NODE);
+        begin_node(&mut structs, "chosen");
+        prop(&mut structs, 15, b"/uart\0");
+        push32(&mut structs, FDT_NOP);
+        prop(&mut structs, 6, b"loglevel=debug nosmp\0");
+        push32(&mut structs, FDT_END_NODE);
+        push32(&mut structs, FDT_END_NODE);
+        push32(&mut structs, FDT_END);
+        wrap(&structs, strings)
+    }
+
+    /// A GIC, a PL011 under a bus with one-cell addresses, and a disabled
+    /// virtio-mmio node.
+    fn devices() -> Vec<u8> {
+        let strings = b"#address-cells\0#size-cells\0interrupt-parent\0compatible\0\
+                        reg\0interrupts\0phandle\0#interrupt-cells\0status\0";
+        let (addr_cells, size_cells, int_parent, compat) = (0, 15, 27, 44);
+        let (reg, interrupts, phandle, int_cells, status) = (55, 59, 70, 78, 95);
+
+        let mut structs = Vec::new();
+        begin_node(&mut structs, "");
+        prop(&mut structs, addr_cells, &cells(&[2]));
+        prop(&mut structs, size_cells, &cells(&[2]));
+        prop(&mut structs, int_parent, &cells(&[1]));
+
+        begin_node(&mut structs, "intc@8000000");
+        prop(&mut structs, compat, b"arm,cortex-a15-gic\0");
+        prop(&mut structs, phandle, &cells(&[1]));
+        prop(&mut structs, int_cells, &cells(&[3]));
+        prop(&mut structs, reg, &cells(&[0, 0x800_0000, 0, 0x1_0000]));
+        push32(&mut structs, FDT_END_NODE);
+
+        begin_node(&mut structs, "soc");
+        prop(&mut structs, addr_cells, &cells(&[1]));
+        prop(&mut structs, size_cells, &cells(&[1]));
+        begin_node(&mut structs, "pl011@9000000");
+        prop(&mut structs, compat, b"arm,pl011\0arm,primecell\0");
+        prop(&mut structs, reg, &cells(&[0x900_0000, 0x1000]));
+        prop(&mut structs, interrupts, &cells(&[0, 1, 4]));
+        push32(&mut structs, FDT_END_NODE);
+        push32(&mut structs, FDT_END_NODE);
+
+        begin_node(&mut structs, "virtio_mmio@a000000");
+        prop(&mut structs, compat, b"virtio,mmio\0");
+        prop(&mut structs, status, b"disabled\0");
+        prop(&mut structs, reg, &cells(&[0, 0xa00_0000, 0, 0x200]));
+        push32(&mut structs, FDT_END_NODE);
+
+        push32(&mut structs, FDT_END_NODE);
+        push32(&mut structs, FDT_END);
+        wrap(&structs, strings)
+    }
+
+    #[test]
+    fn test_bootargs() {
+        let blob = sample();
+        assert_eq!(bootargs(&blob), Some("loglevel=debug nosmp"));
+        assert_eq!(chosen_property(&blob, "stdout-path"), Some(&b"/uart\0"[..]));
+        assert_eq!(chosen_property(&blob, "model"), None);
+    }
+
+    #[test]
+    fn test_rejects_bad_blobs() {
+        let mut blob = sample();
+        blob[0] = 0;
+        assert_eq!(bootargs(&blob), None);
+        let blob = sample();
+        assert_eq!(bootargs(&blob[..HEADER_LEN + 16]), None);
+    }
+
+    #[test]
+    fn test_nodes() {
+        let blob = devices();
+        let fdt = Fdt::new(&blob).unwrap();
+        let names: Vec<_> = fdt.nodes().map(|n| (n.name(), n.depth())).collect();
+        assert_eq!(
+            names,
+            [
+                ("", 0),
+                ("intc@8000000", 1),
+                ("soc", 1),
+                ("pl011@9000000", 2),
+                ("virtio_mmio@a000000", 1)
+            ]
+        );
+
+        let uart = fdt.compatible_nodes("arm,primecell").next().unwrap();
+        assert_eq!(uart.name(), "pl011@9000000");
+        assert_eq!(
+            uart.compatible().collect::<Vec<_>>(),
+            ["arm,pl011", "arm,primecell"]
+        );
+        // One address cell and one size cell from the parent bus.
+        assert_eq!(uart.reg().collect::<Vec<_>>(), [(0x900_0000, 0x1000)]);
+        assert_eq!(uart.interrupts().collect::<Vec<_>>(), [0, 1, 4]);
+        // Inherited from the root.
+        assert_eq!(uart.interrupt_parent(), Some(1));
+
+        let gic = fdt.node_by_phandle(1).unwrap();
+        assert_eq!(gic.name(), "intc@8000000");
+        assert_eq!(gic.u32_property("#interrupt-cells"), Some(3));
+        assert_eq!(gic.reg().collect::<Vec<_>>(), [(0x800_0000, 0x1_0000)]);
+
+        assert_eq!(fdt.compatible_nodes("virtio,mmio").count(), 0);
+        assert!(fdt.chosen().is_none());
+    }
+}
@@ -1,6 +1,9 @@
 //! Kernel utilities
 //!
 //! Miscellaneous helper modules that do not belong to a specific subsystem,
-//! including version information and build metadata.
+//! including version information, build metadata, the boot command line and
+//! the device tree reader it uses.
 
+pub mod cmdline;
+pub mod fdt;
 pub mod version;
@@ -2,6 +2,10 @@
 //!
 //! Provides compile-time version metadata including semantic version,
 //! git hash, and build timestamp. Accessible via the `SYS_VERSION` syscall.
+//!
+//! The build script also embeds a software bill of materials (crate
+//! versions and checksums, toolchain, enabled features) which is exposed as
+//! `/proc/veridian-release`; its short identifier is reported by `uname -v`.
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -14,6 +18,16 @@ pub struct KernelVersionInfo {
     pub supported_archs: u64,
 }
 
+/// Software bill of materials generated by `build.rs`.
+pub const SBOM: &str = include_str!(concat!(env!("OUT_DIR"), "/sbom.txt"));
+
+/// Short identifier of [`SBOM`] (FNV-1a 64 of its contents, hex).
+pub const SBOM_ID: &str = env!("SBOM_ID");
+
+/// `uname -v` string: build flavour plus the SBOM identifier, so an image
+/// can be matched against its published bill of materials.
+pub const UNAME_VERSION: &str = concat!("#1 SMP sbom-", env!("SBOM_ID"));
+
 /// Returns the kernel version information.
 pub fn get_version_info() -> KernelVersionInfo {
     // These values would typically be populated by the build script.
@@ -1,8 +1,9 @@
 //! Image format decoders (TGA, QOI)
 //!
-//! Provides decoders for Truevision TGA and Quite OK Image (QOI) formats.
-//! Both decoders produce `VideoFrame` output using the parent module's
-//! `PixelFormat::Argb8888` for maximum fidelity.
+//! Provides decoders for Truevision TGA and Quite OK Image (QOI) formats;
+//! QOI is decoded by the shared `image-format` crate. Both produce
+//! `VideoFrame` output using the parent module's `PixelFormat::Argb8888`
+//! for maximum fidelity.
 
 #![allow(dead_code, clippy::upper_case_acronyms)]
 
@@ -254,175 +255,20 @@ fn read_tga_pixel(data: &[u8], offset: usize, bpp: usize) -> (u8, u8, u8, u8) {
 // QOI decoder (Quite OK Image format)
 // ---------------------------------------------------------------------------
 
-/// QOI operation tags.
-const QOI_OP_RGB: u8 = 0xFE;
-const QOI_OP_RGBA: u8 = 0xFF;
-const QOI_OP_INDEX_MASK: u8 = 0x00; // 2-bit tag: 00xxxxxx
-const QOI_OP_DIFF_MASK: u8 = 0x40; // 2-bit tag: 01xxxxxx
-const QOI_OP_LUMA_MASK: u8 = 0x80; // 2-bit tag: 10xxxxxx
-const QOI_OP_RUN_MASK: u8 = 0xC0; // 2-bit tag: 11xxxxxx
-
-/// QOI hash function: (r * 3 + g * 5 + b * 7 + a * 11) % 64
-#[inline]
-fn qoi_hash(r: u8, g: u8, b: u8, a: u8) -> usize {
-    ((r as usize) * 3 + (g as usize) * 5 + (b as usize) * 7 + (a as usize) * 11) % 64
-}
-
-/// Decode a QOI (Quite OK Image) file.
-///
-/// QOI specification: <https://qoiformat.org/qoi-specification.pdf>
-///
-/// Header: "qoif" (4B), width (u32 BE), height (u32 BE), channels (u8),
-/// colorspace (u8) End marker: 7 zero bytes + 0x01
+/// Decode a QOI (Quite OK Image) file with the shared `image-format`
+/// codec.
 pub(crate) fn decode_qoi(data: &[u8]) -> Result<VideoFrame, KernelError> {
-    // Minimum: 14 byte header + 8 byte end marker
-    if data.len() < 22 {
-        return Err(KernelError::InvalidArgument {
-            name: "data",
-            value: "QOI data too short",
-        });
-    }
-
-    // Check magic
-    if data[0] != b'q' || data[1] != b'o' || data[2] != b'i' || data[3] != b'f' {
-        return Err(KernelError::InvalidArgument {
-            name: "magic",
-            value: "not a QOI file",
-        });
-    }
-
-    let width = read_be_u32(data, 4);
-    let height = read_be_u32(data, 8);
-    let channels = data[12];
-    let _colorspace = data[13];
-
-    if width == 0 || height == 0 {
-        return Err(KernelError::InvalidArgument {
-            name: "dimensions",
-            value: "zero width or height",
-        });
-    }
-
-    if channels != 3 && channels != 4 {
-        return Err(KernelError::InvalidArgument {
-            name: "channels",
-            value: "must be 3 or 4",
-        });
-    }
-
-    let pixel_count = (width as usize) * (height as usize);
-    let mut frame = VideoFrame::new(width, height, PixelFormat::Argb8888);
-
-    // Previously seen pixel array (64 entries)
-    let mut index: [(u8, u8, u8, u8); 64] = [(0, 0, 0, 0); 64];
-
-    // Current pixel (starts as r=0, g=0, b=0, a=255 per spec)
-    let mut pr: u8 = 0;
-    let mut pg: u8 = 0;
-    let mut pb: u8 = 0;
-    let mut pa: u8 = 255;
-
-    let mut pos: usize = 14; // past header
-    let mut px_idx: usize = 0;
-
-    while px_idx < pixel_count && pos < data.len() {
-        let b1 = data[pos];
-
-        if b1 == QOI_OP_RGB {
-            // RGB literal
-            if pos + 3 >= data.len() {
-                break;
-            }
-            pr = data[pos + 1];
-            pg = data[pos + 2];
-            pb = data[pos + 3];
-            pos += 4;
-        } else if b1 == QOI_OP_RGBA {
-            // RGBA literal
-            if pos + 4 >= data.len() {
-                break;
-            }
-            pr = data[pos + 1];
-            pg = data[pos + 2];
-            pb = data[pos + 3];
-            pa = data[pos + 4];
-            pos += 5;
-        } else {
-            let tag = b1 & 0xC0;
-            match tag {
-                0x00 => {
-                    // QOI_OP_INDEX: 00xxxxxx
-                    let idx = (b1 & 0x3F) as usize;
-                    let (ir, ig, ib, ia) = index[idx];
-                    pr = ir;
-                    pg = ig;
-                    pb = ib;
-                    pa = ia;
-                    pos += 1;
-                }
-                0x40 => {
-                    // QOI_OP_DIFF: 01drr dgg dbb
-                    // dr, dg, db are stored with bias of 2: actual = stored - 2
-                    let dr = ((b1 >> 4) & 0x03) as i8 - 2;
-                    let dg = ((b1 >> 2) & 0x03) as i8 - 2;
-                    let db = (b1 & 0x03) as i8 - 2;
-                    pr = pr.wrapping_add(dr as u8);
-                    pg = pg.wrapping_add(dg as u8);
-                    pb = pb.wrapping_add(db as u8);
-                    pos += 1;
-                }
-                0x80 => {
-                    // QOI_OP_LUMA: 10dddddd followed by one byte: drdg(4) dbdg(4)
-                    if pos + 1 >= data.len() {
-                        break;
-                    }
-                    let b2 = data[pos + 1];
-                    let dg = (b1 & 0x3F) as i8 - 32;
-                    let dr_dg = ((b2 >> 4) & 0x0F) as i8 - 8;
-                    let db_dg = (b2 & 0x0F) as i8 - 8;
-                    let dr = (dr_dg + dg) as u8;
-                    let db = (db_dg + dg) as u8;
-                    pr = pr.wrapping_add(dr);
-                    pg = pg.wrapping_add(dg as u8);
-                    pb = pb.wrapping_add(db);
-                    pos += 2;
-                }
-                0xC0 => {
-                    // QOI_OP_RUN: 11rrrrrr, run length = (rr & 0x3F) + 1 (1..62)
-                    let run = (b1 & 0x3F) as usize + 1;
-                    // Write `run` copies of the current pixel
-                    for _ in 0..run {
-                        if px_idx >= pixel_count {
-                            break;
-                        }
-                        let x = (px_idx % width as usize) as u32;
-                        let y = (px_idx / width as usize) as u32;
-                        frame.set_pixel(x, y, pr, pg, pb, pa);
-                        px_idx += 1;
-                    }
-                    // Update the index for the run pixel
-                    index[qoi_hash(pr, pg, pb, pa)] = (pr, pg, pb, pa);
-                    continue; // already wrote pixels
-                }
-                _ => {
-                    pos += 1;
-                    continue;
-                }
-            }
-        }
-
-        // Store current pixel in index
-        index[qoi_hash(pr, pg, pb, pa)] = (pr, pg, pb, pa);
-
-        // Write one pixel
-        if px_idx < pixel_count {
-            let x = (px_idx % width as usize) as u32;
-            let y = (px_idx / width as usize) as u32;
-            frame.set_pixel(x, y, pr, pg, pb, pa);
-            px_idx += 1;
+    let image = image_format::decode_qoi(data).map_err(|e| KernelError::InvalidArgument {
+        name: "qoi",
+        value: e.as_str(),
+    })?;
+    let mut frame = VideoFrame::new(image.width, image.height, PixelFormat::Argb8888);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let (r, g, b, a) = image.get_pixel(x, y);
+            frame.set_pixel(x, y, r, g, b, a);
         }
     }
-
     Ok(frame)
 }
 
@@ -454,14 +300,6 @@ pub(crate) fn decode_image(data: &[u8]) -> Result<VideoFrame, KernelError> {
 // Byte-reading helpers
 // ---------------------------------------------------------------------------
 
-/// Read a big-endian u32.
-fn read_be_u32(data: &[u8], off: usize) -> u32 {
-    ((data[off] as u32) << 24)
-        | ((data[off + 1] as u32) << 16)
-        | ((data[off + 2] as u32) << 8)
-        | (data[off + 3] as u32)
-}
-
 /// Read a little-endian u16.
 fn read_le_u16(data: &[u8], off: usize) -> u16 {
     (data[off] as u16) | ((data[off + 1] as u16) << 8)
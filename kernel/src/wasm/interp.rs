@@ -0,0 +1,1073 @@
+//! WebAssembly interpreter
+//!
+//! Executes a decoded [`Module`] over an untyped `u64` operand stack. Calls
+//! are handled with an explicit frame stack rather than host recursion, so
+//! guest recursion depth is bounded by [`Config::max_call_depth`] instead of
+//! the kernel stack. An optional fuel counter bounds the number of
+//! instructions executed.
+//!
+//! Integer, memory, control and bulk-memory instructions are implemented.
+//! Floating-point values can be loaded, stored, moved and reinterpreted as
+//! raw bits, but floating-point arithmetic traps: the kernel does not save
+//! FPU state, so it never executes FPU instructions itself.
+
+use alloc::{vec, vec::Vec};
+
+use super::{
+    module::{ExportKind, FuncType, Instr, Module, ValType, MAX_PAGES, PAGE_SIZE},
+    WasmError,
+};
+
+// ---------------------------------------------------------------------------
+// Traps
+// ---------------------------------------------------------------------------
+
+/// Reasons execution stopped abnormally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    /// `unreachable` executed
+    Unreachable,
+    /// Memory access outside linear memory
+    MemoryOutOfBounds,
+    /// Integer division or remainder by zero
+    DivideByZero,
+    /// Signed division overflow
+    IntegerOverflow,
+    /// `call_indirect` index outside the table
+    UndefinedElement,
+    /// `call_indirect` through a null table entry
+    UninitializedElement,
+    /// `call_indirect` signature mismatch
+    IndirectCallTypeMismatch,
+    /// Guest call depth exceeded [`Config::max_call_depth`]
+    CallStackExhausted,
+    /// Operand and local storage exceeded [`Config::max_stack`]
+    StackOverflow,
+    /// Operand stack underflow (ill-typed module)
+    StackUnderflow,
+    /// The fuel budget ran out
+    OutOfFuel,
+    /// Floating-point arithmetic opcode
+    FloatUnsupported(u8),
+    /// Other unsupported instruction (reference types, table operations)
+    Unsupported(u16),
+    /// The guest asked to exit with this status (WASI `proc_exit`)
+    Exit(i32),
+    /// A host function failed
+    Host(&'static str),
+}
+
+impl core::fmt::Display for Trap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unreachable => write!(f, "unreachable executed"),
+            Self::MemoryOutOfBounds => write!(f, "out of bounds memory access"),
+            Self::DivideByZero => write!(f, "integer divide by zero"),
+            Self::IntegerOverflow => write!(f, "integer overflow"),
+            Self::UndefinedElement => write!(f, "undefined table element"),
+            Self::UninitializedElement => write!(f, "uninitialized table element"),
+            Self::IndirectCallTypeMismatch => write!(f, "indirect call type mismatch"),
+            Self::CallStackExhausted => write!(f, "call stack exhausted"),
+            Self::StackOverflow => write!(f, "value stack exhausted"),
+            Self::StackUnderflow => write!(f, "value stack underflow"),
+            Self::OutOfFuel => write!(f, "fuel exhausted"),
+            Self::FloatUnsupported(op) => {
+                write!(f, "floating-point opcode {:#04x} not supported", op)
+            }
+            Self::Unsupported(op) => write!(f, "opcode {:#x} not supported", op),
+            Self::Exit(code) => write!(f, "exit({})", code),
+            Self::Host(msg) => write!(f, "host error: {}", msg),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Values
+// ---------------------------------------------------------------------------
+
+/// A typed value crossing the host/guest boundary. Floats are carried as
+/// their IEEE 754 bit patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(u32),
+    F64(u64),
+}
+
+impl Value {
+    /// Value type of this value
+    pub fn ty(self) -> ValType {
+        match self {
+            Self::I32(_) => ValType::I32,
+            Self::I64(_) => ValType::I64,
+            Self::F32(_) => ValType::F32,
+            Self::F64(_) => ValType::F64,
+        }
+    }
+
+    /// Parse a decimal argument of type `ty`. Floats are given as raw bits
+    /// (decimal or `0x` hex), matching how they are displayed.
+    pub fn parse(ty: ValType, s: &str) -> Option<Self> {
+        let bits = |s: &str| match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => s.parse::<u64>().ok(),
+        };
+        match ty {
+            ValType::I32 => s
+                .parse::<i32>()
+                .ok()
+                .or_else(|| s.parse::<u32>().ok().map(|v| v as i32))
+                .map(Self::I32),
+            ValType::I64 => s
+                .parse::<i64>()
+                .ok()
+                .or_else(|| s.parse::<u64>().ok().map(|v| v as i64))
+                .map(Self::I64),
+            ValType::F32 => bits(s).and_then(|b| u32::try_from(b).ok()).map(Self::F32),
+            ValType::F64 => bits(s).map(Self::F64),
+            ValType::FuncRef | ValType::ExternRef => None,
+        }
+    }
+
+    fn to_bits(self) -> u64 {
+        match self {
+            Self::I32(v) => v as u32 as u64,
+            Self::I64(v) => v as u64,
+            Self::F32(v) => v as u64,
+            Self::F64(v) => v,
+        }
+    }
+
+    fn from_bits(ty: ValType, bits: u64) -> Self {
+        match ty {
+            ValType::I64 => Self::I64(bits as i64),
+            ValType::F32 => Self::F32(bits as u32),
+            ValType::F64 => Self::F64(bits),
+            // References are table indices; expose them as i32.
+            ValType::I32 | ValType::FuncRef | ValType::ExternRef => Self::I32(bits as i32),
+        }
+    }
+}
+
+impl core::fmt::Display for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::I32(v) => write!(f, "{}", v),
+            Self::I64(v) => write!(f, "{}", v),
+            Self::F32(bits) => write!(f, "f32:{:#010x}", bits),
+            Self::F64(bits) => write!(f, "f64:{:#018x}", bits),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Linear Memory
+// ---------------------------------------------------------------------------
+
+/// An instance's linear memory
+#[derive(Debug, Default)]
+pub struct Memory {
+    data: Vec<u8>,
+    max_pages: u32,
+}
+
+impl Memory {
+    /// Current size in pages.
+    pub fn size_pages(&self) -> u32 {
+        (self.data.len() / PAGE_SIZE) as u32
+    }
+
+    /// Current size in bytes.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether the instance has no (or zero-sized) memory.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn range(&self, addr: u64, len: u64) -> Result<core::ops::Range<usize>, Trap> {
+        match addr.checked_add(len) {
+            Some(end) if end <= self.data.len() as u64 => Ok(addr as usize..end as usize),
+            _ => Err(Trap::MemoryOutOfBounds),
+        }
+    }
+
+    /// Borrow `len` bytes at guest address `addr`.
+    pub fn bytes(&self, addr: u32, len: u32) -> Result<&[u8], Trap> {
+        let r = self.range(addr as u64, len as u64)?;
+        Ok(&self.data[r])
+    }
+
+    /// Mutably borrow `len` bytes at guest address `addr`.
+    pub fn bytes_mut(&mut self, addr: u32, len: u32) -> Result<&mut [u8], Trap> {
+        let r = self.range(addr as u64, len as u64)?;
+        Ok(&mut self.data[r])
+    }
+
+    /// Read a little-endian `u32`.
+    pub fn read_u32(&self, addr: u32) -> Result<u32, Trap> {
+        Ok(self.load(addr as u64, 4)? as u32)
+    }
+
+    /// Write a little-endian `u32`.
+    pub fn write_u32(&mut self, addr: u32, value: u32) -> Result<(), Trap> {
+        self.store(addr as u64, 4, value as u64)
+    }
+
+    /// Write a little-endian `u64`.
+    pub fn write_u64(&mut self, addr: u32, value: u64) -> Result<(), Trap> {
+        self.store(addr as u64, 8, value)
+    }
+
+    fn load(&self, addr: u64, size: usize) -> Result<u64, Trap> {
+        let r = self.range(addr, size as u64)?;
+        let mut buf = [0u8; 8];
+        buf[..size].copy_from_slice(&self.data[r]);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn store(&mut self, addr: u64, size: usize, value: u64) -> Result<(), Trap> {
+        let r = self.range(addr, size as u64)?;
+        self.data[r].copy_from_slice(&value.to_le_bytes()[..size]);
+        Ok(())
+    }
+
+    /// Grow by `delta` pages, returning the old size, or `None` if the
+    /// limit would be exceeded.
+    fn grow(&mut self, delta: u32) -> Option<u32> {
+        let old = self.size_pages();
+        let new = old.checked_add(delta)?;
+        if new > self.max_pages {
+            return None;
+        }
+        self.data.resize(new as usize * PAGE_SIZE, 0);
+        Some(old)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Host Interface
+// ---------------------------------------------------------------------------
+
+/// Provider of imported functions
+pub trait Host {
+    /// Resolve import `module.name` with signature `ty` to a host function
+    /// id, or `None` to fail instantiation. Host functions return at most
+    /// one value.
+    fn resolve(&mut self, module: &str, name: &str, ty: &FuncType) -> Option<usize>;
+
+    /// Call host function `id` with raw argument bits in parameter order.
+    fn call(&mut self, id: usize, args: &[u64], memory: &mut Memory) -> Result<Option<u64>, Trap>;
+}
+
+/// Resource limits for an instance
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// Largest linear memory, in 64 KiB pages
+    pub max_pages: u32,
+    /// Instruction budget; `None` for unlimited
+    pub fuel: Option<u64>,
+    /// Deepest guest call stack
+    pub max_call_depth: usize,
+    /// Most operand stack slots plus locals across all frames
+    pub max_stack: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_pages: 256,
+            fuel: None,
+            max_call_depth: 1024,
+            max_stack: 1 << 20,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Instance
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+struct Label {
+    /// Operand stack height below the block's parameters
+    height: usize,
+    /// Values carried by a branch to this label
+    arity: usize,
+    /// Instruction index a branch continues at
+    cont: usize,
+    is_loop: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    /// Index into `Module::functions`
+    func: usize,
+    pc: usize,
+    /// Base of this frame's locals
+    locals: usize,
+    /// Base of this frame's labels
+    labels: usize,
+    /// Operand stack height at entry, after arguments were moved to locals
+    height: usize,
+    arity: usize,
+}
+
+/// Execution stacks for one top-level call
+#[derive(Default)]
+struct Stacks {
+    values: Vec<u64>,
+    locals: Vec<u64>,
+    labels: Vec<Label>,
+    frames: Vec<Frame>,
+}
+
+impl Stacks {
+    fn pop(&mut self) -> Result<u64, Trap> {
+        self.values.pop().ok_or(Trap::StackUnderflow)
+    }
+
+    fn pop32(&mut self) -> Result<u32, Trap> {
+        Ok(self.pop()? as u32)
+    }
+
+    fn pop_n(&mut self, n: usize) -> Result<Vec<u64>, Trap> {
+        let at = self
+            .values
+            .len()
+            .checked_sub(n)
+            .ok_or(Trap::StackUnderflow)?;
+        Ok(self.values.split_off(at))
+    }
+
+    /// Keep the top `arity` values and drop everything above `height`
+    /// beneath them.
+    fn unwind(&mut self, height: usize, arity: usize) -> Result<(), Trap> {
+        let top = self
+            .values
+            .len()
+            .checked_sub(arity)
+            .filter(|&t| t >= height)
+            .ok_or(Trap::StackUnderflow)?;
+        self.values.drain(height..top);
+        Ok(())
+    }
+
+    fn push_label(
+        &mut self,
+        params: u32,
+        arity: u32,
+        cont: usize,
+        is_loop: bool,
+    ) -> Result<(), Trap> {
+        let height = self
+            .values
+            .len()
+            .checked_sub(params as usize)
+            .ok_or(Trap::StackUnderflow)?;
+        self.labels.push(Label {
+            height,
+            arity: arity as usize,
+            cont,
+            is_loop,
+        });
+        Ok(())
+    }
+
+    fn ret(&mut self) -> Result<(), Trap> {
+        let frame = self.frames.pop().ok_or(Trap::StackUnderflow)?;
+        self.unwind(frame.height, frame.arity)?;
+        self.locals.truncate(frame.locals);
+        self.labels.truncate(frame.labels);
+        Ok(())
+    }
+
+    fn branch(&mut self, depth: u32) -> Result<(), Trap> {
+        let fi = self.frames.len() - 1;
+        let open = self.labels.len() - self.frames[fi].labels;
+        if depth as usize >= open {
+            // Branch to the function body's own label
+            return self.ret();
+        }
+        let li = self.labels.len() - 1 - depth as usize;
+        let label = self.labels[li];
+        self.unwind(label.height, label.arity)?;
+        self.labels
+            .truncate(if label.is_loop { li + 1 } else { li });
+        self.frames[fi].pc = label.cont;
+        Ok(())
+    }
+}
+
+/// An instantiated module with its memory, globals, table and host
+pub struct Instance<H: Host> {
+    module: Module,
+    host: H,
+    /// Host function id for each import
+    host_funcs: Vec<usize>,
+    memory: Memory,
+    globals: Vec<u64>,
+    table: Vec<Option<u32>>,
+    /// Dropped (or already applied active) data segments
+    dropped: Vec<bool>,
+    config: Config,
+    fuel: Option<u64>,
+}
+
+impl<H: Host> Instance<H> {
+    /// Link `module` against `host`, initialise memory, globals and table,
+    /// and run the start function.
+    pub fn new(module: Module, mut host: H, config: Config) -> Result<Self, WasmError> {
+        let mut host_funcs = Vec::with_capacity(module.imports.len());
+        for import in &module.imports {
+            let ty = &module.types[import.type_idx as usize];
+            if ty.results.len() > 1 {
+                return Err(WasmError::Link {
+                    module: import.module.clone(),
+                    name: import.name.clone(),
+                });
+            }
+            match host.resolve(&import.module, &import.name, ty) {
+                Some(id) => host_funcs.push(id),
+                None => {
+                    return Err(WasmError::Link {
+                        module: import.module.clone(),
+                        name: import.name.clone(),
+                    })
+                }
+            }
+        }
+
+        let mut memory = Memory::default();
+        if let Some(limits) = module.memory {
+            let max = limits.max.unwrap_or(MAX_PAGES).min(config.max_pages);
+            if limits.min > max {
+                return Err(WasmError::Instantiate("memory exceeds limit"));
+            }
+            memory = Memory {
+                data: vec![0; limits.min as usize * PAGE_SIZE],
+                max_pages: max,
+            };
+        }
+
+        let mut table = match module.table {
+            Some(limits) => vec![None; limits.min as usize],
+            None => Vec::new(),
+        };
+        for segment in &module.elements {
+            let start = segment.offset as usize;
+            let end = start + segment.funcs.len();
+            if end > table.len() {
+                return Err(WasmError::Instantiate("element segment out of bounds"));
+            }
+            table[start..end].copy_from_slice(&segment.funcs);
+        }
+
+        for segment in &module.data {
+            if let Some(offset) = segment.offset {
+                memory
+                    .bytes_mut(offset, segment.bytes.len() as u32)
+                    .map_err(|_| WasmError::Instantiate("data segment out of bounds"))?
+                    .copy_from_slice(&segment.bytes);
+            }
+        }
+
+        let mut instance = Self {
+            globals: module.globals.iter().map(|g| g.init).collect(),
+            dropped: module.data.iter().map(|d| d.offset.is_some()).collect(),
+            module,
+            host,
+            host_funcs,
+            memory,
+            table,
+            config,
+            fuel: config.fuel,
+        };
+
+        if let Some(start) = instance.module.start {
+            if instance.module.func_type(start) != Some(&FuncType::default()) {
+                return Err(WasmError::Instantiate(
+                    "start function has parameters or results",
+                ));
+            }
+            instance.call(start, Vec::new()).map_err(WasmError::Trap)?;
+        }
+        Ok(instance)
+    }
+
+    /// Call exported function `name`.
+    pub fn invoke(&mut self, name: &str, args: &[Value]) -> Result<Vec<Value>, WasmError> {
+        let idx = match self.module.export(name) {
+            Some(e) if e.kind == ExportKind::Func => e.index,
+            _ => return Err(WasmError::NoSuchExport(name.into())),
+        };
+        let ty = self
+            .module
+            .func_type(idx)
+            .cloned()
+            .ok_or(WasmError::Instantiate("export of unknown function"))?;
+        if args.len() != ty.params.len() || args.iter().zip(&ty.params).any(|(a, &t)| a.ty() != t) {
+            return Err(WasmError::Signature(name.into()));
+        }
+        let results = self
+            .call(idx, args.iter().map(|a| a.to_bits()).collect())
+            .map_err(WasmError::Trap)?;
+        Ok(results
+            .iter()
+            .zip(&ty.results)
+            .map(|(&bits, &t)| Value::from_bits(t, bits))
+            .collect())
+    }
+
+    /// The decoded module.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// The host.
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    /// The host, mutably.
+    pub fn host_mut(&mut self) -> &mut H {
+        &mut self.host
+    }
+
+    /// Linear memory.
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Linear memory, mutably.
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    /// Remaining fuel, if metered.
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Replace the remaining fuel.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Reset the fuel to the configured budget.
+    pub fn refuel(&mut self) {
+        self.fuel = self.config.fuel;
+    }
+
+    /// Tear down the instance, returning the host.
+    pub fn into_host(self) -> H {
+        self.host
+    }
+
+    /// Run function `func` (imports first) to completion.
+    fn call(&mut self, func: u32, args: Vec<u64>) -> Result<Vec<u64>, Trap> {
+        let Instance {
+            module,
+            host,
+            host_funcs,
+            memory,
+            globals,
+            table,
+            dropped,
+            config,
+            fuel,
+        } = self;
+        let imports = module.imports.len();
+
+        if (func as usize) < imports {
+            let result = host.call(host_funcs[func as usize], &args, memory)?;
+            return Ok(result.into_iter().collect());
+        }
+
+        let mut st = Stacks {
+            values: args,
+            ..Stacks::default()
+        };
+        enter(module, config, &mut st, func as usize - imports)?;
+
+        while let Some(frame) = st.frames.last_mut() {
+            let func = &module.functions[frame.func];
+            let instr = *func.body.get(frame.pc).ok_or(Trap::StackUnderflow)?;
+            frame.pc += 1;
+            let locals_base = frame.locals;
+            let label_base = frame.labels;
+            let fi = st.frames.len() - 1;
+
+            if let Some(f) = fuel {
+                *f = f.checked_sub(1).ok_or(Trap::OutOfFuel)?;
+            }
+            if st.values.len() + st.locals.len() > config.max_stack {
+                return Err(Trap::StackOverflow);
+            }
+
+            match instr {
+                Instr::Unreachable => return Err(Trap::Unreachable),
+                Instr::Nop => {}
+                Instr::Block { sig, end } => {
+                    st.push_label(sig.params, sig.results, end as usize + 1, false)?
+                }
+                Instr::Loop { sig } => {
+                    let pc = st.frames[fi].pc;
+                    st.push_label(sig.params, sig.params, pc, true)?
+                }
+                Instr::If { sig, else_, end } => {
+                    if st.pop32()? != 0 {
+                        st.push_label(sig.params, sig.results, end as usize + 1, false)?;
+                    } else if let Some(else_) = else_ {
+                        st.push_label(sig.params, sig.results, end as usize + 1, false)?;
+                        st.frames[fi].pc = else_ as usize + 1;
+                    } else {
+                        st.frames[fi].pc = end as usize + 1;
+                    }
+                }
+                Instr::Else { end } => {
+                    st.labels.pop();
+                    st.frames[fi].pc = end as usize + 1;
+                }
+                Instr::End => {
+                    if st.labels.len() == label_base {
+                        st.ret()?;
+                    } else {
+                        st.labels.pop();
+                    }
+                }
+                Instr::Br(depth) => st.branch(depth)?,
+                Instr::BrIf(depth) => {
+                    if st.pop32()? != 0 {
+                        st.branch(depth)?;
+                    }
+                }
+                Instr::BrTable(idx) => {
+                    let targets = &func.br_tables[idx as usize];
+                    let i = st.pop32()? as usize;
+                    let depth = targets.get(i).unwrap_or(&targets[targets.len() - 1]);
+                    st.branch(*depth)?;
+                }
+                Instr::Return => st.ret()?,
+                Instr::Call(idx) => {
+                    call_func(module, host, host_funcs, memory, config, &mut st, idx)?
+                }
+                Instr::CallIndirect(type_idx) => {
+                    let i = st.pop32()? as usize;
+                    let callee = table
+                        .get(i)
+                        .ok_or(Trap::UndefinedElement)?
+                        .ok_or(Trap::UninitializedElement)?;
+                    if module.func_type(callee) != module.types.get(type_idx as usize) {
+                        return Err(Trap::IndirectCallTypeMismatch);
+                    }
+                    call_func(module, host, host_funcs, memory, config, &mut st, callee)?;
+                }
+                Instr::Drop => {
+                    st.pop()?;
+                }
+                Instr::Select => {
+                    let c = st.pop32()?;
+                    let b = st.pop()?;
+                    let a = st.pop()?;
+                    st.values.push(if c != 0 { a } else { b });
+                }
+                Instr::LocalGet(i) => {
+                    let v = st.locals[locals_base + i as usize];
+                    st.values.push(v);
+                }
+                Instr::LocalSet(i) => {
+                    let v = st.pop()?;
+                    st.locals[locals_base + i as usize] = v;
+                }
+                Instr::LocalTee(i) => {
+                    let v = *st.values.last().ok_or(Trap::StackUnderflow)?;
+                    st.locals[locals_base + i as usize] = v;
+                }
+                Instr::GlobalGet(i) => st.values.push(globals[i as usize]),
+                Instr::GlobalSet(i) => globals[i as usize] = st.pop()?,
+                Instr::Load { op, offset } => {
+                    let addr = st.pop32()? as u64 + offset as u64;
+                    let v = load(memory, op, addr)?;
+                    st.values.push(v);
+                }
+                Instr::Store { op, offset } => {
+                    let v = st.pop()?;
+                    let addr = st.pop32()? as u64 + offset as u64;
+                    let size = match op {
+                        0x37 | 0x39 => 8,
+                        0x36 | 0x38 | 0x3E => 4,
+                        0x3B | 0x3D => 2,
+                        _ => 1,
+                    };
+                    memory.store(addr, size, v)?;
+                }
+                Instr::MemorySize => st.values.push(memory.size_pages() as u64),
+                Instr::MemoryGrow => {
+                    let delta = st.pop32()?;
+                    let old = memory.grow(delta).unwrap_or(u32::MAX);
+                    st.values.push(old as u64);
+                }
+                Instr::MemoryInit(seg) => {
+                    let n = st.pop32()?;
+                    let src = st.pop32()? as usize;
+                    let dst = st.pop32()?;
+                    let data: &[u8] = if dropped[seg as usize] {
+                        &[]
+                    } else {
+                        &module.data[seg as usize].bytes
+                    };
+                    let bytes = src
+                        .checked_add(n as usize)
+                        .and_then(|end| data.get(src..end))
+                        .ok_or(Trap::MemoryOutOfBounds)?;
+                    memory.bytes_mut(dst, n)?.copy_from_slice(bytes);
+                }
+                Instr::DataDrop(seg) => dropped[seg as usize] = true,
+                Instr::MemoryCopy => {
+                    let n = st.pop32()? as u64;
+                    let src = memory.range(st.pop32()? as u64, n)?;
+                    let dst = memory.range(st.pop32()? as u64, n)?;
+                    memory.data.copy_within(src, dst.start);
+                }
+                Instr::MemoryFill => {
+                    let n = st.pop32()?;
+                    let value = st.pop32()? as u8;
+                    let dst = st.pop32()?;
+                    memory.bytes_mut(dst, n)?.fill(value);
+                }
+                Instr::Const(bits) => st.values.push(bits),
+                Instr::Numeric(op) => numeric(op, &mut st)?,
+                Instr::Unsupported(op) => return Err(Trap::Unsupported(op)),
+            }
+        }
+
+        Ok(st.values)
+    }
+}
+
+/// Push a frame for module-defined function `local` (index into
+/// `Module::functions`), moving its arguments into locals.
+fn enter(module: &Module, config: &Config, st: &mut Stacks, local: usize) -> Result<(), Trap> {
+    if st.frames.len() >= config.max_call_depth {
+        return Err(Trap::CallStackExhausted);
+    }
+    let func = &module.functions[local];
+    let ty = &module.types[func.type_idx as usize];
+    let args = st.pop_n(ty.params.len())?;
+    let base = st.locals.len();
+    st.locals.extend_from_slice(&args);
+    st.locals
+        .resize(base + args.len() + func.locals as usize, 0);
+    st.frames.push(Frame {
+        func: local,
+        pc: 0,
+        locals: base,
+        labels: st.labels.len(),
+        height: st.values.len(),
+        arity: ty.results.len(),
+    });
+    Ok(())
+}
+
+/// Call function `idx` (imports first) from inside the interpreter loop.
+fn call_func<H: Host>(
+    module: &Module,
+    host: &mut H,
+    host_funcs: &[usize],
+    memory: &mut Memory,
+    config: &Config,
+    st: &mut Stacks,
+    idx: u32,
+) -> Result<(), Trap> {
+    let imports = module.imports.len();
+    if (idx as usize) < imports {
+        let ty = &module.types[module.imports[idx as usize].type_idx as usize];
+        let args = st.pop_n(ty.params.len())?;
+        let result = host.call(host_funcs[idx as usize], &args, memory)?;
+        match (result, ty.results.len()) {
+            (Some(v), 1) => st.values.push(v),
+            (None, 0) => {}
+            _ => return Err(Trap::Host("host function returned wrong arity")),
+        }
+        Ok(())
+    } else {
+        enter(module, config, st, idx as usize - imports)
+    }
+}
+
+fn load(memory: &Memory, op: u8, addr: u64) -> Result<u64, Trap> {
+    Ok(match op {
+        0x28 | 0x2A | 0x35 => memory.load(addr, 4)?,
+        0x29 | 0x2B => memory.load(addr, 8)?,
+        0x2C => memory.load(addr, 1)? as u8 as i8 as i32 as u32 as u64,
+        0x2D | 0x31 => memory.load(addr, 1)?,
+        0x2E => memory.load(addr, 2)? as u16 as i16 as i32 as u32 as u64,
+        0x2F | 0x33 => memory.load(addr, 2)?,
+        0x30 => memory.load(addr, 1)? as u8 as i8 as i64 as u64,
+        0x32 => memory.load(addr, 2)? as u16 as i16 as i64 as u64,
+        0x34 => memory.load(addr, 4)? as u32 as i32 as i64 as u64,
+        _ => return Err(Trap::Unsupported(op as u16)),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Numeric Instructions
+// ---------------------------------------------------------------------------
+
+fn numeric(op: u8, st: &mut Stacks) -> Result<(), Trap> {
+    let v = match op {
+        0x45 => (st.pop32()? == 0) as u64,
+        0x46..=0x4F => {
+            let b = st.pop32()?;
+            let a = st.pop32()?;
+            compare(
+                op - 0x46,
+                a as u64,
+                b as u64,
+                a as i32 as i64,
+                b as i32 as i64,
+            ) as u64
+        }
+        0x50 => (st.pop()? == 0) as u64,
+        0x51..=0x5A => {
+            let b = st.pop()?;
+            let a = st.pop()?;
+            compare(op - 0x51, a, b, a as i64, b as i64) as u64
+        }
+        0x67 => st.pop32()?.leading_zeros() as u64,
+        0x68 => st.pop32()?.trailing_zeros() as u64,
+        0x69 => st.pop32()?.count_ones() as u64,
+        0x6A..=0x78 => {
+            let b = st.pop32()?;
+            let a = st.pop32()?;
+            binary32(op, a, b)? as u64
+        }
+        0x79 => st.pop()?.leading_zeros() as u64,
+        0x7A => st.pop()?.trailing_zeros() as u64,
+        0x7B => st.pop()?.count_ones() as u64,
+        0x7C..=0x8A => {
+            let b = st.pop()?;
+            let a = st.pop()?;
+            binary64(op, a, b)?
+        }
+        // i32.wrap_i64
+        0xA7 => st.pop32()? as u64,
+        // i64.extend_i32_s / _u
+        0xAC => st.pop32()? as i32 as i64 as u64,
+        0xAD => st.pop32()? as u64,
+        // Reinterpretations leave the bits alone
+        0xBC..=0xBF => return Ok(()),
+        // Sign extension
+        0xC0 => st.pop32()? as i8 as i32 as u32 as u64,
+        0xC1 => st.pop32()? as i16 as i32 as u32 as u64,
+        0xC2 => st.pop()? as i8 as i64 as u64,
+        0xC3 => st.pop()? as i16 as i64 as u64,
+        0xC4 => st.pop()? as i32 as i64 as u64,
+        _ => return Err(Trap::FloatUnsupported(op)),
+    };
+    st.values.push(v);
+    Ok(())
+}
+
+/// Comparison `kind`: eq, ne, lt_s, lt_u, gt_s, gt_u, le_s, le_u, ge_s,
+/// ge_u.
+fn compare(kind: u8, a: u64, b: u64, sa: i64, sb: i64) -> bool {
+    match kind {
+        0 => a == b,
+        1 => a != b,
+        2 => sa < sb,
+        3 => a < b,
+        4 => sa > sb,
+        5 => a > b,
+        6 => sa <= sb,
+        7 => a <= b,
+        8 => sa >= sb,
+        _ => a >= b,
+    }
+}
+
+fn binary32(op: u8, a: u32, b: u32) -> Result<u32, Trap> {
+    let (sa, sb) = (a as i32, b as i32);
+    Ok(match op {
+        0x6A => a.wrapping_add(b),
+        0x6B => a.wrapping_sub(b),
+        0x6C => a.wrapping_mul(b),
+        0x6D => {
+            if sb == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            sa.checked_div(sb).ok_or(Trap::IntegerOverflow)? as u32
+        }
+        0x6E => a.checked_div(b).ok_or(Trap::DivideByZero)?,
+        0x6F => {
+            if sb == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            sa.wrapping_rem(sb) as u32
+        }
+        0x70 => a.checked_rem(b).ok_or(Trap::DivideByZero)?,
+        0x71 => a & b,
+        0x72 => a | b,
+        0x73 => a ^ b,
+        0x74 => a.wrapping_shl(b),
+        0x75 => sa.wrapping_shr(b) as u32,
+        0x76 => a.wrapping_shr(b),
+        0x77 => a.rotate_left(b % 32),
+        _ => a.rotate_right(b % 32),
+    })
+}
+
+fn binary64(op: u8, a: u64, b: u64) -> Result<u64, Trap> {
+    let (sa, sb) = (a as i64, b as i64);
+    Ok(match op {
+        0x7C => a.wrapping_add(b),
+        0x7D => a.wrapping_sub(b),
+        0x7E => a.wrapping_mul(b),
+        0x7F => {
+            if sb == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            sa.checked_div(sb).ok_or(Trap::IntegerOverflow)? as u64
+        }
+        0x80 => a.checked_div(b).ok_or(Trap::DivideByZero)?,
+        0x81 => {
+            if sb == 0 {
+                return Err(Trap::DivideByZero);
+            }
+            sa.wrapping_rem(sb) as u64
+        }
+        0x82 => a.checked_rem(b).ok_or(Trap::DivideByZero)?,
+        0x83 => a & b,
+        0x84 => a | b,
+        0x85 => a ^ b,
+        0x86 => a.wrapping_shl(b as u32),
+        0x87 => sa.wrapping_shr(b as u32) as u64,
+        0x88 => a.wrapping_shr(b as u32),
+        0x89 => a.rotate_left((b % 64) as u32),
+        _ => a.rotate_right((b % 64) as u32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::{
+        super::module::{encode_module, encode_uleb},
+        *,
+    };
+
+    struct NoHost;
+
+    impl Host for NoHost {
+        fn resolve(&mut self, _: &str, _: &str, _: &FuncType) -> Option<usize> {
+            None
+        }
+
+        fn call(&mut self, _: usize, _: &[u64], _: &mut Memory) -> Result<Option<u64>, Trap> {
+            Err(Trap::Host("no host"))
+        }
+    }
+
+    /// Module with one exported function `f` of type `ty` (encoded) and
+    /// `body` (locals + code, without size prefix), plus one memory page.
+    fn single_function(ty: &[u8], body: &[u8]) -> Instance<NoHost> {
+        let mut types = vec![0x01, 0x60];
+        types.extend_from_slice(ty);
+        let mut code = vec![0x01];
+        encode_uleb(&mut code, body.len() as u64);
+        code.extend_from_slice(body);
+        let bin = encode_module(&[
+            (1, types),
+            (3, vec![0x01, 0x00]),
+            (5, vec![0x01, 0x00, 0x01]),
+            (7, vec![0x01, 0x01, b'f', 0x00, 0x00]),
+            (10, code),
+        ]);
+        Instance::new(Module::decode(&bin).unwrap(), NoHost, Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // (func (param i32 i32) (result i32) local.get 0 local.get 1 i32.sub)
+        let mut inst = single_function(
+            &[0x02, 0x7F, 0x7F, 0x01, 0x7F],
+            &[0x00, 0x20, 0x00, 0x20, 0x01, 0x6B, 0x0B],
+        );
+        assert_eq!(
+            inst.invoke("f", &[Value::I32(2), Value::I32(5)]).unwrap(),
+            vec![Value::I32(-3)]
+        );
+        assert!(matches!(
+            inst.invoke("f", &[Value::I32(2)]),
+            Err(WasmError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn test_loop_factorial() {
+        // (func (param i64) (result i64) (local i64)
+        //   i64.const 1 local.set 1
+        //   block loop
+        //     local.get 0 i64.eqz br_if 1
+        //     local.get 1 local.get 0 i64.mul local.set 1
+        //     local.get 0 i64.const 1 i64.sub local.set 0
+        //     br 0
+        //   end end
+        //   local.get 1)
+        let body = [
+            0x01, 0x01, 0x7E, // one i64 local
+            0x42, 0x01, 0x21, 0x01, // acc = 1
+            0x02, 0x40, 0x03, 0x40, // block loop
+            0x20, 0x00, 0x50, 0x0D, 0x01, // n == 0 -> exit
+            0x20, 0x01, 0x20, 0x00, 0x7E, 0x21, 0x01, // acc *= n
+            0x20, 0x00, 0x42, 0x01, 0x7D, 0x21, 0x00, // n -= 1
+            0x0C, 0x00, 0x0B, 0x0B, // br 0, end, end
+            0x20, 0x01, 0x0B,
+        ];
+        let mut inst = single_function(&[0x01, 0x7E, 0x01, 0x7E], &body);
+        assert_eq!(
+            inst.invoke("f", &[Value::I64(20)]).unwrap(),
+            vec![Value::I64(2_432_902_008_176_640_000)]
+        );
+
+        inst.set_fuel(Some(50));
+        assert!(matches!(
+            inst.invoke("f", &[Value::I64(20)]),
+            Err(WasmError::Trap(Trap::OutOfFuel))
+        ));
+    }
+
+    #[test]
+    fn test_memory_traps() {
+        // (func (param i32) (result i32) local.get 0 i32.load offset=0)
+        let mut inst = single_function(
+            &[0x01, 0x7F, 0x01, 0x7F],
+            &[0x00, 0x20, 0x00, 0x28, 0x02, 0x00, 0x0B],
+        );
+        inst.memory_mut().write_u32(8, 0xDEAD_BEEF).unwrap();
+        assert_eq!(
+            inst.invoke("f", &[Value::I32(8)]).unwrap(),
+            vec![Value::I32(0xDEAD_BEEFu32 as i32)]
+        );
+        assert!(matches!(
+            inst.invoke("f", &[Value::I32(PAGE_SIZE as i32 - 2)]),
+            Err(WasmError::Trap(Trap::MemoryOutOfBounds))
+        ));
+    }
+
+    #[test]
+    fn test_division_traps() {
+        assert_eq!(
+            binary32(0x6D, i32::MIN as u32, -1i32 as u32),
+            Err(Trap::IntegerOverflow)
+        );
+        assert_eq!(binary32(0x6F, i32::MIN as u32, -1i32 as u32), Ok(0));
+        assert_eq!(binary64(0x80, 1, 0), Err(Trap::DivideByZero));
+        assert_eq!(binary32(0x75, -8i32 as u32, 33), Ok(-4i32 as u32));
+    }
+}
@@ -0,0 +1,190 @@
+//! WebAssembly Runtime for VeridianOS
+//!
+//! Runs untrusted WebAssembly modules inside the kernel's sandbox: WASI
+//! command-line programs (`_start`) and long-lived extensions that export
+//! functions for the shell, editor or other services to call.
+//!
+//! Execution is interpreted (no JIT), metered by fuel and bounded in memory,
+//! call depth and operand stack. Floating-point arithmetic traps, in line
+//! with the rest of the kernel's no-FPU policy. The only system interface is
+//! the capability-gated WASI host in [`wasi`]; nothing else is importable.
+
+pub mod interp;
+pub mod module;
+pub mod wasi;
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+pub use interp::{Config, Host, Instance, Memory, Trap, Value};
+pub use module::{DecodeError, FuncType, Module, ValType};
+use spin::Mutex;
+pub use wasi::{WasiCaps, WasiCtx};
+
+/// Default instruction budget for one command or extension call.
+pub const DEFAULT_FUEL: u64 = 100_000_000;
+
+/// Most extensions loaded at once.
+const MAX_EXTENSIONS: usize = 32;
+
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+/// Errors from loading or running a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WasmError {
+    /// The binary is malformed
+    Decode(DecodeError),
+    /// An import the host does not provide (or with the wrong signature)
+    Link { module: String, name: String },
+    /// Instantiation failed: limits exceeded or segments out of bounds
+    Instantiate(&'static str),
+    /// Execution trapped
+    Trap(Trap),
+    /// No exported function with this name
+    NoSuchExport(String),
+    /// Arguments do not match the export's signature
+    Signature(String),
+    /// Extension registry error
+    Extension(&'static str),
+}
+
+impl fmt::Display for WasmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "decode error: {}", e),
+            Self::Link { module, name } => write!(f, "unresolved import {}::{}", module, name),
+            Self::Instantiate(reason) => write!(f, "instantiation failed: {}", reason),
+            Self::Trap(trap) => write!(f, "trap: {}", trap),
+            Self::NoSuchExport(name) => write!(f, "no exported function '{}'", name),
+            Self::Signature(name) => write!(f, "argument mismatch calling '{}'", name),
+            Self::Extension(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl From<DecodeError> for WasmError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Run a WASI command module to completion.
+///
+/// Calls `_start` and returns the exit status (0 if it returns normally,
+/// the `proc_exit` code otherwise) together with the context, so captured
+/// output and denial counts can be inspected.
+pub fn run_command(
+    bytes: &[u8],
+    ctx: WasiCtx,
+    config: Config,
+) -> Result<(i32, WasiCtx), WasmError> {
+    let module = Module::decode(bytes)?;
+    let mut instance = match Instance::new(module, ctx, config) {
+        Ok(instance) => instance,
+        Err(WasmError::Trap(Trap::Exit(code))) => {
+            // proc_exit from the start function; the context is gone with
+            // the half-built instance.
+            return Ok((code, WasiCtx::new(Vec::new())));
+        }
+        Err(e) => return Err(e),
+    };
+    let code = match instance.invoke("_start", &[]) {
+        Ok(_) => 0,
+        Err(WasmError::Trap(Trap::Exit(code))) => code,
+        Err(e) => return Err(e),
+    };
+    Ok((code, instance.into_host()))
+}
+
+// ---------------------------------------------------------------------------
+// Extensions
+// ---------------------------------------------------------------------------
+
+static EXTENSIONS: Mutex<BTreeMap<String, Instance<WasiCtx>>> = Mutex::new(BTreeMap::new());
+
+/// Instantiate `bytes` as extension `name`.
+///
+/// Output is captured and returned by [`call_extension`]. A WASI reactor's
+/// `_initialize` export is run once after instantiation.
+pub fn load_extension(
+    name: &str,
+    bytes: &[u8],
+    ctx: WasiCtx,
+    config: Config,
+) -> Result<(), WasmError> {
+    {
+        let extensions = EXTENSIONS.lock();
+        if extensions.contains_key(name) {
+            return Err(WasmError::Extension("extension already loaded"));
+        }
+        if extensions.len() >= MAX_EXTENSIONS {
+            return Err(WasmError::Extension("too many extensions loaded"));
+        }
+    }
+
+    let module = Module::decode(bytes)?;
+    let mut instance = Instance::new(module, ctx.capture_output(), config)?;
+    if instance.module().export("_initialize").is_some() {
+        instance.invoke("_initialize", &[])?;
+    }
+
+    let mut extensions = EXTENSIONS.lock();
+    if extensions.contains_key(name) {
+        return Err(WasmError::Extension("extension already loaded"));
+    }
+    extensions.insert(name.to_string(), instance);
+    Ok(())
+}
+
+/// Remove extension `name`. Returns whether it was loaded.
+pub fn unload_extension(name: &str) -> bool {
+    EXTENSIONS.lock().remove(name).is_some()
+}
+
+/// Call `export` on extension `name` with a fresh fuel budget.
+///
+/// Returns the results and anything the call wrote to stdout or stderr.
+pub fn call_extension(
+    name: &str,
+    export: &str,
+    args: &[Value],
+) -> Result<(Vec<Value>, Vec<u8>), WasmError> {
+    let mut extensions = EXTENSIONS.lock();
+    let instance = extensions
+        .get_mut(name)
+        .ok_or(WasmError::Extension("no such extension"))?;
+    instance.refuel();
+    let result = instance.invoke(export, args);
+    let output = instance.host_mut().take_output();
+    Ok((result?, output))
+}
+
+/// Signature of function export `export` of extension `name`.
+pub fn extension_signature(name: &str, export: &str) -> Result<FuncType, WasmError> {
+    let extensions = EXTENSIONS.lock();
+    let module = extensions
+        .get(name)
+        .ok_or(WasmError::Extension("no such extension"))?
+        .module();
+    module
+        .export(export)
+        .filter(|e| e.kind == module::ExportKind::Func)
+        .and_then(|e| module.func_type(e.index))
+        .cloned()
+        .ok_or_else(|| WasmError::NoSuchExport(export.to_string()))
+}
+
+/// Names of loaded extensions.
+pub fn extensions() -> Vec<String> {
+    EXTENSIONS.lock().keys().cloned().collect()
+}
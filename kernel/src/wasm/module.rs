@@ -0,0 +1,1189 @@
+//! WebAssembly binary decoder
+//!
+//! Parses a `.wasm` binary (MVP plus the sign-extension, bulk-memory and
+//! multi-value proposals) into a [`Module`]. Function bodies are decoded
+//! once into [`Instr`] vectors with block targets resolved to instruction
+//! indices, so the interpreter never touches LEB128 at run time.
+//!
+//! Decoding checks structure and every index (locals, globals, functions,
+//! types, branch depths, data segments), but does not type-check the
+//! operand stack. The interpreter bounds-checks every stack, local, memory
+//! and table access, so an ill-typed module can only trap.
+
+use alloc::{collections::BTreeSet, string::String, vec::Vec};
+
+// ---------------------------------------------------------------------------
+// Limits
+// ---------------------------------------------------------------------------
+
+/// WebAssembly page size.
+pub const PAGE_SIZE: usize = 65536;
+
+/// Largest number of pages a 32-bit memory can address.
+pub const MAX_PAGES: u32 = 65536;
+
+/// Largest accepted module binary.
+pub const MAX_MODULE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Largest number of locals (including parameters) per function.
+pub const MAX_LOCALS: u32 = 50_000;
+
+/// Largest table, in elements.
+pub const MAX_TABLE_SIZE: u32 = 1 << 20;
+
+const MAGIC: &[u8; 4] = b"\0asm";
+const VERSION: u32 = 1;
+
+// ---------------------------------------------------------------------------
+// Decode Error
+// ---------------------------------------------------------------------------
+
+/// Error produced while decoding a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Byte offset in the binary where decoding failed
+    pub offset: usize,
+    /// What was wrong
+    pub reason: &'static str,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at offset {:#x}", self.reason, self.offset)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Module Structure
+// ---------------------------------------------------------------------------
+
+/// Value types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+    FuncRef,
+    ExternRef,
+}
+
+impl ValType {
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0x7F => Some(Self::I32),
+            0x7E => Some(Self::I64),
+            0x7D => Some(Self::F32),
+            0x7C => Some(Self::F64),
+            0x70 => Some(Self::FuncRef),
+            0x6F => Some(Self::ExternRef),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for ValType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::FuncRef => "funcref",
+            Self::ExternRef => "externref",
+        })
+    }
+}
+
+/// Function signature
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+impl core::fmt::Display for FuncType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let list = |f: &mut core::fmt::Formatter<'_>, types: &[ValType]| {
+            f.write_str("(")?;
+            for (i, ty) in types.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", ty)?;
+            }
+            f.write_str(")")
+        };
+        list(f, &self.params)?;
+        f.write_str(" -> ")?;
+        list(f, &self.results)
+    }
+}
+
+/// An imported function. Table, memory and global imports are not
+/// supported.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub type_idx: u32,
+}
+
+/// Size limits of a memory (in pages) or table (in elements)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub min: u32,
+    pub max: Option<u32>,
+}
+
+/// A module-defined global
+#[derive(Debug, Clone, Copy)]
+pub struct Global {
+    pub ty: ValType,
+    pub mutable: bool,
+    /// Initial value as raw bits
+    pub init: u64,
+}
+
+/// Export kinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Func,
+    Table,
+    Memory,
+    Global,
+}
+
+/// An exported item
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub name: String,
+    pub kind: ExportKind,
+    pub index: u32,
+}
+
+/// An active element segment for table 0. Passive and declarative
+/// segments only matter to table instructions, which are not supported.
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub offset: u32,
+    pub funcs: Vec<Option<u32>>,
+}
+
+/// A data segment
+#[derive(Debug, Clone)]
+pub struct Data {
+    /// Offset into memory 0 for active segments; `None` for passive ones
+    pub offset: Option<u32>,
+    pub bytes: Vec<u8>,
+}
+
+/// Block signature, resolved to operand counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockSig {
+    pub params: u32,
+    pub results: u32,
+}
+
+/// A decoded instruction
+///
+/// Numeric instructions without immediates keep their opcode byte and are
+/// dispatched by the interpreter; floating-point arithmetic among them
+/// traps, since the kernel does not use the FPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    Unreachable,
+    Nop,
+    /// `end` is the index of the matching `End`
+    Block {
+        sig: BlockSig,
+        end: u32,
+    },
+    Loop {
+        sig: BlockSig,
+    },
+    /// `else_` is the index of the matching `Else`, if any
+    If {
+        sig: BlockSig,
+        else_: Option<u32>,
+        end: u32,
+    },
+    Else {
+        end: u32,
+    },
+    End,
+    Br(u32),
+    BrIf(u32),
+    /// Index into [`Function::br_tables`]
+    BrTable(u32),
+    Return,
+    Call(u32),
+    CallIndirect(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    /// Load with opcode 0x28..=0x35 and static offset
+    Load {
+        op: u8,
+        offset: u32,
+    },
+    /// Store with opcode 0x36..=0x3E and static offset
+    Store {
+        op: u8,
+        offset: u32,
+    },
+    MemorySize,
+    MemoryGrow,
+    MemoryInit(u32),
+    DataDrop(u32),
+    MemoryCopy,
+    MemoryFill,
+    /// `i32/i64/f32/f64.const` as raw bits
+    Const(u64),
+    /// Numeric opcode 0x45..=0xC4
+    Numeric(u8),
+    /// Recognised but unsupported instruction (reference types, table
+    /// operations, saturating float truncation)
+    Unsupported(u16),
+}
+
+/// A module-defined function
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub type_idx: u32,
+    /// Declared locals, excluding parameters
+    pub locals: u32,
+    pub body: Vec<Instr>,
+    /// Target lists of `br_table` instructions, default target last
+    pub br_tables: Vec<Vec<u32>>,
+}
+
+/// A decoded module
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    pub types: Vec<FuncType>,
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    pub table: Option<Limits>,
+    pub memory: Option<Limits>,
+    pub globals: Vec<Global>,
+    pub exports: Vec<Export>,
+    pub start: Option<u32>,
+    pub elements: Vec<Element>,
+    pub data: Vec<Data>,
+}
+
+impl Module {
+    /// Decode and validate a module binary.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() > MAX_MODULE_SIZE {
+            return Err(DecodeError {
+                offset: 0,
+                reason: "module too large",
+            });
+        }
+        Decoder::new(bytes).module()
+    }
+
+    /// Signature of function `idx` (imports first).
+    pub fn func_type(&self, idx: u32) -> Option<&FuncType> {
+        let idx = idx as usize;
+        let type_idx = match idx.checked_sub(self.imports.len()) {
+            None => self.imports[idx].type_idx,
+            Some(local) => self.functions.get(local)?.type_idx,
+        };
+        self.types.get(type_idx as usize)
+    }
+
+    /// Number of functions including imports.
+    pub fn func_count(&self) -> usize {
+        self.imports.len() + self.functions.len()
+    }
+
+    /// Look up an export by name.
+    pub fn export(&self, name: &str) -> Option<&Export> {
+        self.exports.iter().find(|e| e.name == name)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Decoder
+// ---------------------------------------------------------------------------
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn err<T>(&self, reason: &'static str) -> Result<T, DecodeError> {
+        Err(DecodeError {
+            offset: self.pos,
+            reason,
+        })
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        match self.bytes.get(self.pos) {
+            Some(&b) => {
+                self.pos += 1;
+                Ok(b)
+            }
+            None => self.err("unexpected end of input"),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        match self.pos.checked_add(n) {
+            Some(end) if end <= self.bytes.len() => {
+                let s = &self.bytes[self.pos..end];
+                self.pos = end;
+                Ok(s)
+            }
+            _ => self.err("unexpected end of input"),
+        }
+    }
+
+    /// Unsigned LEB128 of at most `bits` bits.
+    fn uleb(&mut self, bits: u32) -> Result<u64, DecodeError> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift >= bits || (shift + 7 > bits && (b & 0x7F) >> (bits - shift) != 0) {
+                return self.err("integer too large");
+            }
+            result |= u64::from(b & 0x7F) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Signed LEB128 of at most `bits` bits, sign-extended to 64.
+    fn sleb(&mut self, bits: u32) -> Result<i64, DecodeError> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift >= bits {
+                return self.err("integer too large");
+            }
+            result |= i64::from(b & 0x7F) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                if shift < 64 && b & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                // Unused high bits of the last byte must match the sign.
+                if shift > bits && bits < 64 {
+                    let v = result >> (bits - 1);
+                    if v != 0 && v != -1 {
+                        return self.err("integer too large");
+                    }
+                }
+                return Ok(result);
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(self.uleb(32)? as u32)
+    }
+
+    /// Vector length, bounded by the remaining input so a hostile count
+    /// cannot trigger a huge allocation.
+    fn count(&mut self) -> Result<usize, DecodeError> {
+        let n = self.u32()? as usize;
+        if n > self.bytes.len() - self.pos {
+            return self.err("vector length exceeds input");
+        }
+        Ok(n)
+    }
+
+    fn name(&mut self) -> Result<String, DecodeError> {
+        let len = self.count()?;
+        let bytes = self.take(len)?;
+        match core::str::from_utf8(bytes) {
+            Ok(s) => Ok(String::from(s)),
+            Err(_) => self.err("name is not UTF-8"),
+        }
+    }
+
+    fn valtype(&mut self) -> Result<ValType, DecodeError> {
+        let b = self.byte()?;
+        match ValType::from_byte(b) {
+            Some(t) => Ok(t),
+            None => self.err("invalid value type"),
+        }
+    }
+
+    fn limits(&mut self, ceiling: u32) -> Result<Limits, DecodeError> {
+        let flags = self.byte()?;
+        let min = self.u32()?;
+        let max = match flags {
+            0 => None,
+            1 => Some(self.u32()?),
+            _ => return self.err("shared or 64-bit limits are not supported"),
+        };
+        if min > ceiling || max.is_some_and(|m| m > ceiling || m < min) {
+            return self.err("limits out of range");
+        }
+        Ok(Limits { min, max })
+    }
+
+    fn module(mut self) -> Result<Module, DecodeError> {
+        if self.take(4)? != MAGIC {
+            return self.err("not a WebAssembly module");
+        }
+        let version = self.take(4)?;
+        if u32::from_le_bytes([version[0], version[1], version[2], version[3]]) != VERSION {
+            return self.err("unsupported version");
+        }
+
+        let mut module = Module::default();
+        let mut func_types: Vec<u32> = Vec::new();
+        let mut data_count: Option<u32> = None;
+        let mut seen = BTreeSet::new();
+        let mut code_seen = false;
+
+        while !self.at_end() {
+            let id = self.byte()?;
+            let size = self.u32()? as usize;
+            let start = self.pos;
+            let end = match start.checked_add(size) {
+                Some(end) if end <= self.bytes.len() => end,
+                _ => return self.err("section extends past end of module"),
+            };
+            if id != 0 && !seen.insert(id) {
+                return self.err("duplicate section");
+            }
+            let mut section = Decoder {
+                bytes: &self.bytes[..end],
+                pos: start,
+            };
+            match id {
+                0 => {}
+                1 => section.type_section(&mut module)?,
+                2 => section.import_section(&mut module)?,
+                3 => {
+                    for _ in 0..section.count()? {
+                        let idx = section.u32()?;
+                        if idx as usize >= module.types.len() {
+                            return section.err("unknown type");
+                        }
+                        func_types.push(idx);
+                    }
+                }
+                4 => {
+                    for _ in 0..section.count()? {
+                        if module.table.is_some() {
+                            return section.err("multiple tables are not supported");
+                        }
+                        if section.valtype()? != ValType::FuncRef {
+                            return section.err("only funcref tables are supported");
+                        }
+                        module.table = Some(section.limits(MAX_TABLE_SIZE)?);
+                    }
+                }
+                5 => {
+                    for _ in 0..section.count()? {
+                        if module.memory.is_some() {
+                            return section.err("multiple memories are not supported");
+                        }
+                        module.memory = Some(section.limits(MAX_PAGES)?);
+                    }
+                }
+                6 => {
+                    for _ in 0..section.count()? {
+                        let ty = section.valtype()?;
+                        let mutable = match section.byte()? {
+                            0 => false,
+                            1 => true,
+                            _ => return section.err("invalid mutability"),
+                        };
+                        let init = section.const_expr(&module.globals)?;
+                        module.globals.push(Global { ty, mutable, init });
+                    }
+                }
+                7 => section.export_section(&mut module, func_types.len())?,
+                8 => {
+                    let idx = section.u32()?;
+                    if idx as usize >= module.imports.len() + func_types.len() {
+                        return section.err("unknown start function");
+                    }
+                    module.start = Some(idx);
+                }
+                9 => section.element_section(&mut module, func_types.len())?,
+                10 => {
+                    let n = section.count()?;
+                    if n != func_types.len() {
+                        return section.err("function and code section counts differ");
+                    }
+                    for &type_idx in &func_types {
+                        let size = section.u32()? as usize;
+                        let body_end = match section.pos.checked_add(size) {
+                            Some(e) if e <= end => e,
+                            _ => return section.err("function body past end of section"),
+                        };
+                        let mut body = Decoder {
+                            bytes: &self.bytes[..body_end],
+                            pos: section.pos,
+                        };
+                        let func = body.function(&module, type_idx, &func_types, data_count)?;
+                        module.functions.push(func);
+                        section.pos = body_end;
+                    }
+                    code_seen = true;
+                }
+                11 => {
+                    let n = section.count()?;
+                    if data_count.is_some_and(|c| c as usize != n) {
+                        return section.err("data count mismatch");
+                    }
+                    for _ in 0..n {
+                        let offset = match section.u32()? {
+                            0 => Some(section.const_expr(&module.globals)? as u32),
+                            1 => None,
+                            2 => {
+                                if section.u32()? != 0 {
+                                    return section.err("unknown memory");
+                                }
+                                Some(section.const_expr(&module.globals)? as u32)
+                            }
+                            _ => return section.err("invalid data segment flags"),
+                        };
+                        if offset.is_some() && module.memory.is_none() {
+                            return section.err("data segment without memory");
+                        }
+                        let len = section.count()?;
+                        let bytes = section.take(len)?.to_vec();
+                        module.data.push(Data { offset, bytes });
+                    }
+                }
+                12 => {
+                    if code_seen {
+                        return section.err("data count section after code");
+                    }
+                    data_count = Some(section.u32()?);
+                }
+                _ => return section.err("unknown section"),
+            }
+            if id != 0 && section.pos != end {
+                return section.err("section size mismatch");
+            }
+            self.pos = end;
+        }
+
+        if module.functions.len() != func_types.len() {
+            return self.err("function section without code");
+        }
+        Ok(module)
+    }
+
+    fn type_section(&mut self, module: &mut Module) -> Result<(), DecodeError> {
+        for _ in 0..self.count()? {
+            if self.byte()? != 0x60 {
+                return self.err("expected function type");
+            }
+            let mut ty = FuncType::default();
+            for _ in 0..self.count()? {
+                ty.params.push(self.valtype()?);
+            }
+            for _ in 0..self.count()? {
+                ty.results.push(self.valtype()?);
+            }
+            module.types.push(ty);
+        }
+        Ok(())
+    }
+
+    fn import_section(&mut self, module: &mut Module) -> Result<(), DecodeError> {
+        for _ in 0..self.count()? {
+            let module_name = self.name()?;
+            let name = self.name()?;
+            if self.byte()? != 0 {
+                return self.err("only function imports are supported");
+            }
+            let type_idx = self.u32()?;
+            if type_idx as usize >= module.types.len() {
+                return self.err("unknown type");
+            }
+            module.imports.push(Import {
+                module: module_name,
+                name,
+                type_idx,
+            });
+        }
+        Ok(())
+    }
+
+    fn export_section(&mut self, module: &mut Module, defined: usize) -> Result<(), DecodeError> {
+        for _ in 0..self.count()? {
+            let name = self.name()?;
+            let (kind, bound) = match self.byte()? {
+                0 => (ExportKind::Func, module.imports.len() + defined),
+                1 => (ExportKind::Table, module.table.is_some() as usize),
+                2 => (ExportKind::Memory, module.memory.is_some() as usize),
+                3 => (ExportKind::Global, module.globals.len()),
+                _ => return self.err("invalid export kind"),
+            };
+            let index = self.u32()?;
+            if index as usize >= bound {
+                return self.err("export of unknown item");
+            }
+            if module.exports.iter().any(|e| e.name == name) {
+                return self.err("duplicate export name");
+            }
+            module.exports.push(Export { name, kind, index });
+        }
+        Ok(())
+    }
+
+    fn element_section(&mut self, module: &mut Module, defined: usize) -> Result<(), DecodeError> {
+        let funcs_total = module.imports.len() + defined;
+        for _ in 0..self.count()? {
+            let flags = self.u32()?;
+            if flags > 7 {
+                return self.err("invalid element segment flags");
+            }
+            let passive_or_declarative = flags & 1 != 0;
+            let explicit_table = flags & 2 != 0;
+            let uses_exprs = flags & 4 != 0;
+
+            let offset = if passive_or_declarative {
+                None
+            } else {
+                if explicit_table && self.u32()? != 0 {
+                    return self.err("unknown table");
+                }
+                Some(self.const_expr(&module.globals)? as u32)
+            };
+            if passive_or_declarative || explicit_table {
+                // elemkind (0x00 = funcref) or reftype
+                let kind = self.byte()?;
+                if kind != 0x00 && kind != 0x70 {
+                    return self.err("only funcref elements are supported");
+                }
+            }
+
+            let mut funcs = Vec::new();
+            for _ in 0..self.count()? {
+                let func = if uses_exprs {
+                    self.ref_expr(funcs_total)?
+                } else {
+                    let idx = self.u32()?;
+                    if idx as usize >= funcs_total {
+                        return self.err("unknown function");
+                    }
+                    Some(idx)
+                };
+                funcs.push(func);
+            }
+            if let Some(offset) = offset {
+                if module.table.is_none() {
+                    return self.err("element segment without table");
+                }
+                module.elements.push(Element { offset, funcs });
+            }
+        }
+        Ok(())
+    }
+
+    /// `ref.func idx end` or `ref.null func end`.
+    fn ref_expr(&mut self, funcs_total: usize) -> Result<Option<u32>, DecodeError> {
+        let value = match self.byte()? {
+            0xD2 => {
+                let idx = self.u32()?;
+                if idx as usize >= funcs_total {
+                    return self.err("unknown function");
+                }
+                Some(idx)
+            }
+            0xD0 => {
+                self.byte()?;
+                None
+            }
+            _ => return self.err("unsupported element expression"),
+        };
+        if self.byte()? != 0x0B {
+            return self.err("expected end of constant expression");
+        }
+        Ok(value)
+    }
+
+    /// Evaluate a constant expression to raw bits. `global.get` may name any
+    /// earlier global (extended-const).
+    fn const_expr(&mut self, globals: &[Global]) -> Result<u64, DecodeError> {
+        let value = match self.byte()? {
+            0x41 => self.sleb(32)? as i32 as u32 as u64,
+            0x42 => self.sleb(64)? as u64,
+            0x43 => {
+                let b = self.take(4)?;
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64
+            }
+            0x44 => {
+                let b = self.take(8)?;
+                let mut a = [0u8; 8];
+                a.copy_from_slice(b);
+                u64::from_le_bytes(a)
+            }
+            0x23 => {
+                let idx = self.u32()? as usize;
+                match globals.get(idx) {
+                    Some(g) if !g.mutable => g.init,
+                    _ => return self.err("constant expression reads unknown or mutable global"),
+                }
+            }
+            _ => return self.err("unsupported constant expression"),
+        };
+        if self.byte()? != 0x0B {
+            return self.err("expected end of constant expression");
+        }
+        Ok(value)
+    }
+
+    fn block_sig(&mut self, types: &[FuncType]) -> Result<BlockSig, DecodeError> {
+        let b = *self.bytes.get(self.pos).ok_or(DecodeError {
+            offset: self.pos,
+            reason: "unexpected end of input",
+        })?;
+        if b == 0x40 {
+            self.pos += 1;
+            return Ok(BlockSig {
+                params: 0,
+                results: 0,
+            });
+        }
+        if ValType::from_byte(b).is_some() {
+            self.pos += 1;
+            return Ok(BlockSig {
+                params: 0,
+                results: 1,
+            });
+        }
+        let idx = self.sleb(33)?;
+        match usize::try_from(idx).ok().and_then(|i| types.get(i)) {
+            Some(ty) => Ok(BlockSig {
+                params: ty.params.len() as u32,
+                results: ty.results.len() as u32,
+            }),
+            None => self.err("unknown block type"),
+        }
+    }
+
+    /// `memarg`: alignment hint (ignored) and static offset.
+    fn memarg(&mut self, module: &Module) -> Result<u32, DecodeError> {
+        if module.memory.is_none() {
+            return self.err("memory instruction without memory");
+        }
+        let align = self.u32()?;
+        if align >= 32 {
+            return self.err("invalid alignment");
+        }
+        self.u32()
+    }
+
+    fn function(
+        &mut self,
+        module: &Module,
+        type_idx: u32,
+        func_types: &[u32],
+        data_count: Option<u32>,
+    ) -> Result<Function, DecodeError> {
+        let params = module.types[type_idx as usize].params.len() as u32;
+        let mut locals = 0u32;
+        for _ in 0..self.count()? {
+            let n = self.u32()?;
+            self.valtype()?;
+            locals = match locals.checked_add(n) {
+                Some(l) if l.saturating_add(params) <= MAX_LOCALS => l,
+                _ => return self.err("too many locals"),
+            };
+        }
+        let local_count = params + locals;
+        let funcs_total = module.imports.len() + func_types.len();
+
+        let mut body: Vec<Instr> = Vec::new();
+        let mut br_tables: Vec<Vec<u32>> = Vec::new();
+        // Indices of open Block/Loop/If instructions
+        let mut open: Vec<usize> = Vec::new();
+
+        loop {
+            let op = self.byte()?;
+            let depth = open.len() as u32;
+            let branch = |d: &mut Decoder<'_>, target: u32| {
+                if target > depth {
+                    d.err("branch depth out of range")
+                } else {
+                    Ok(target)
+                }
+            };
+            let instr = match op {
+                0x00 => Instr::Unreachable,
+                0x01 => Instr::Nop,
+                0x02 => {
+                    open.push(body.len());
+                    Instr::Block {
+                        sig: self.block_sig(&module.types)?,
+                        end: 0,
+                    }
+                }
+                0x03 => {
+                    open.push(body.len());
+                    Instr::Loop {
+                        sig: self.block_sig(&module.types)?,
+                    }
+                }
+                0x04 => {
+                    open.push(body.len());
+                    Instr::If {
+                        sig: self.block_sig(&module.types)?,
+                        else_: None,
+                        end: 0,
+                    }
+                }
+                0x05 => {
+                    let here = body.len() as u32;
+                    match open.last().map(|&i| &mut body[i]) {
+                        Some(Instr::If {
+                            else_: e @ None, ..
+                        }) => *e = Some(here),
+                        _ => return self.err("else without if"),
+                    }
+                    Instr::Else { end: 0 }
+                }
+                0x0B => {
+                    let here = body.len() as u32;
+                    match open.pop() {
+                        Some(start) => {
+                            let mut else_pos = None;
+                            match &mut body[start] {
+                                Instr::Block { end, .. } => *end = here,
+                                Instr::If { else_, end, .. } => {
+                                    *end = here;
+                                    else_pos = *else_;
+                                }
+                                _ => {}
+                            }
+                            if let Some(e) = else_pos {
+                                body[e as usize] = Instr::Else { end: here };
+                            }
+                            Instr::End
+                        }
+                        None => {
+                            body.push(Instr::End);
+                            break;
+                        }
+                    }
+                }
+                0x0C => {
+                    let target = self.u32()?;
+                    Instr::Br(branch(self, target)?)
+                }
+                0x0D => {
+                    let target = self.u32()?;
+                    Instr::BrIf(branch(self, target)?)
+                }
+                0x0E => {
+                    let n = self.count()?;
+                    let mut targets = Vec::with_capacity(n + 1);
+                    for _ in 0..=n {
+                        let t = self.u32()?;
+                        targets.push(branch(self, t)?);
+                    }
+                    br_tables.push(targets);
+                    Instr::BrTable(br_tables.len() as u32 - 1)
+                }
+                0x0F => Instr::Return,
+                0x10 => {
+                    let idx = self.u32()?;
+                    if idx as usize >= funcs_total {
+                        return self.err("unknown function");
+                    }
+                    Instr::Call(idx)
+                }
+                0x11 => {
+                    let ty = self.u32()?;
+                    if ty as usize >= module.types.len() {
+                        return self.err("unknown type");
+                    }
+                    if self.u32()? != 0 || module.table.is_none() {
+                        return self.err("unknown table");
+                    }
+                    Instr::CallIndirect(ty)
+                }
+                0x1A => Instr::Drop,
+                0x1B => Instr::Select,
+                0x1C => {
+                    for _ in 0..self.count()? {
+                        self.valtype()?;
+                    }
+                    Instr::Select
+                }
+                0x20..=0x22 => {
+                    let idx = self.u32()?;
+                    if idx >= local_count {
+                        return self.err("unknown local");
+                    }
+                    match op {
+                        0x20 => Instr::LocalGet(idx),
+                        0x21 => Instr::LocalSet(idx),
+                        _ => Instr::LocalTee(idx),
+                    }
+                }
+                0x23 | 0x24 => {
+                    let idx = self.u32()?;
+                    match module.globals.get(idx as usize) {
+                        None => return self.err("unknown global"),
+                        Some(g) if op == 0x24 && !g.mutable => {
+                            return self.err("global is immutable")
+                        }
+                        _ => {}
+                    }
+                    if op == 0x23 {
+                        Instr::GlobalGet(idx)
+                    } else {
+                        Instr::GlobalSet(idx)
+                    }
+                }
+                0x25 | 0x26 => {
+                    self.u32()?;
+                    Instr::Unsupported(op as u16)
+                }
+                0x28..=0x35 => Instr::Load {
+                    op,
+                    offset: self.memarg(module)?,
+                },
+                0x36..=0x3E => Instr::Store {
+                    op,
+                    offset: self.memarg(module)?,
+                },
+                0x3F | 0x40 => {
+                    if self.u32()? != 0 || module.memory.is_none() {
+                        return self.err("unknown memory");
+                    }
+                    if op == 0x3F {
+                        Instr::MemorySize
+                    } else {
+                        Instr::MemoryGrow
+                    }
+                }
+                0x41 => Instr::Const(self.sleb(32)? as i32 as u32 as u64),
+                0x42 => Instr::Const(self.sleb(64)? as u64),
+                0x43 => {
+                    let b = self.take(4)?;
+                    Instr::Const(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64)
+                }
+                0x44 => {
+                    let mut a = [0u8; 8];
+                    a.copy_from_slice(self.take(8)?);
+                    Instr::Const(u64::from_le_bytes(a))
+                }
+                0x45..=0xC4 => Instr::Numeric(op),
+                0xD0 => {
+                    self.byte()?;
+                    Instr::Unsupported(op as u16)
+                }
+                0xD1 => Instr::Unsupported(op as u16),
+                0xD2 => {
+                    if self.u32()? as usize >= funcs_total {
+                        return self.err("unknown function");
+                    }
+                    Instr::Unsupported(op as u16)
+                }
+                0xFC => self.prefixed(module, data_count)?,
+                _ => return self.err("unknown opcode"),
+            };
+            body.push(instr);
+        }
+
+        if self.pos != self.bytes.len() {
+            return self.err("function body continues past end");
+        }
+        Ok(Function {
+            type_idx,
+            locals,
+            body,
+            br_tables,
+        })
+    }
+
+    /// 0xFC-prefixed instructions.
+    fn prefixed(&mut self, module: &Module, data_count: Option<u32>) -> Result<Instr, DecodeError> {
+        let sub = self.u32()?;
+        let data_idx = |d: &mut Decoder<'_>| {
+            let idx = d.u32()?;
+            match data_count {
+                Some(n) if idx < n => Ok(idx),
+                _ => d.err("unknown data segment"),
+            }
+        };
+        let memory = |d: &mut Decoder<'_>| {
+            if d.byte()? != 0 || module.memory.is_none() {
+                d.err("unknown memory")
+            } else {
+                Ok(())
+            }
+        };
+        Ok(match sub {
+            0..=7 => Instr::Unsupported(0xFC00 | sub as u16),
+            8 => {
+                let idx = data_idx(self)?;
+                memory(self)?;
+                Instr::MemoryInit(idx)
+            }
+            9 => Instr::DataDrop(data_idx(self)?),
+            10 => {
+                memory(self)?;
+                memory(self)?;
+                Instr::MemoryCopy
+            }
+            11 => {
+                memory(self)?;
+                Instr::MemoryFill
+            }
+            12 | 14 => {
+                self.u32()?;
+                self.u32()?;
+                Instr::Unsupported(0xFC00 | sub as u16)
+            }
+            13 | 15..=17 => {
+                self.u32()?;
+                Instr::Unsupported(0xFC00 | sub as u16)
+            }
+            _ => return self.err("unknown opcode"),
+        })
+    }
+}
+
+/// Build a minimal module binary from sections, for tests and tools.
+#[cfg(test)]
+pub(crate) fn encode_module(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut out = alloc::vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+    for (id, body) in sections {
+        out.push(*id);
+        encode_uleb(&mut out, body.len() as u64);
+        out.extend_from_slice(body);
+    }
+    out
+}
+
+#[cfg(test)]
+pub(crate) fn encode_uleb(out: &mut Vec<u8>, mut v: u64) {
+    loop {
+        let b = (v & 0x7F) as u8;
+        v >>= 7;
+        if v == 0 {
+            out.push(b);
+            return;
+        }
+        out.push(b | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_leb128() {
+        let mut d = Decoder::new(&[0xE5, 0x8E, 0x26]);
+        assert_eq!(d.uleb(32), Ok(624485));
+        let mut d = Decoder::new(&[0xC0, 0xBB, 0x78]);
+        assert_eq!(d.sleb(32), Ok(-123456));
+        let mut d = Decoder::new(&[0x7F]);
+        assert_eq!(d.sleb(32), Ok(-1));
+        // Five bytes with bits beyond 32 set
+        let mut d = Decoder::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+        assert!(d.uleb(32).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_header() {
+        assert!(Module::decode(b"\0asm").is_err());
+        assert!(Module::decode(b"\x7fELF\x01\x00\x00\x00").is_err());
+        assert!(Module::decode(&[0, 0x61, 0x73, 0x6D, 2, 0, 0, 0]).is_err());
+        assert!(Module::decode(&[0, 0x61, 0x73, 0x6D, 1, 0, 0, 0]).is_ok());
+    }
+
+    #[test]
+    fn test_decode_blocks() {
+        // (func (result i32) (block (result i32) (i32.const 1) (if (then nop)
+        //   (else nop))) )
+        let body = vec![
+            0x00, // no locals
+            0x02, 0x7F, 0x41, 0x01, 0x41, 0x00, 0x04, 0x40, 0x01, 0x05, 0x01, 0x0B, 0x0B, 0x0B,
+        ];
+        let mut code = vec![0x01];
+        encode_uleb(&mut code, body.len() as u64);
+        code.extend_from_slice(&body);
+        let bin = encode_module(&[
+            (1, vec![0x01, 0x60, 0x00, 0x01, 0x7F]),
+            (3, vec![0x01, 0x00]),
+            (10, code),
+        ]);
+        let module = Module::decode(&bin).unwrap();
+        let f = &module.functions[0];
+        assert_eq!(
+            f.body[0],
+            Instr::Block {
+                sig: BlockSig {
+                    params: 0,
+                    results: 1
+                },
+                end: 8
+            }
+        );
+        assert_eq!(
+            f.body[3],
+            Instr::If {
+                sig: BlockSig {
+                    params: 0,
+                    results: 0
+                },
+                else_: Some(5),
+                end: 7
+            }
+        );
+        assert_eq!(f.body[5], Instr::Else { end: 7 });
+        assert_eq!(f.body.len(), 10);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_indices() {
+        // local.get 1 in a function with no locals
+        let bin = encode_module(&[
+            (1, vec![0x01, 0x60, 0x00, 0x00]),
+            (3, vec![0x01, 0x00]),
+            (10, vec![0x01, 0x05, 0x00, 0x20, 0x01, 0x1A, 0x0B]),
+        ]);
+        assert_eq!(Module::decode(&bin).unwrap_err().reason, "unknown local");
+
+        // br 1 at depth 0
+        let bin = encode_module(&[
+            (1, vec![0x01, 0x60, 0x00, 0x00]),
+            (3, vec![0x01, 0x00]),
+            (10, vec![0x01, 0x04, 0x00, 0x0C, 0x01, 0x0B]),
+        ]);
+        assert_eq!(
+            Module::decode(&bin).unwrap_err().reason,
+            "branch depth out of range"
+        );
+    }
+}
@@ -0,0 +1,808 @@
+//! WASI host (`wasi_snapshot_preview1`)
+//!
+//! Implements the subset of WASI preview 1 that command-line tools and
+//! plugins built with wasi-libc or Rust's `wasm32-wasip1` target need at
+//! startup and for basic I/O. Everything is denied unless granted:
+//!
+//! - stdio, the clocks and the random source are each gated by a [`WasiCaps`]
+//!   bit;
+//! - the file system is reachable only through preopened directories, each
+//!   carrying capability [`Rights`] (`READ`, `WRITE`, `CREATE`). Paths are
+//!   resolved relative to the preopen and may not escape it.
+//!
+//! Denied calls return `ENOTCAPABLE` and are counted in
+//! [`WasiCtx::denials`]. Imports from `wasi_snapshot_preview1` that are not
+//! implemented link to a stub returning `ENOSYS`, so modules that merely
+//! reference them still load.
+
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+
+use super::{
+    interp::{Host, Memory, Trap},
+    module::{FuncType, ValType},
+};
+use crate::{
+    cap::Rights,
+    fs::{NodeType, Permissions, VfsNode},
+};
+
+/// Import module name for WASI preview 1
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+/// Most open descriptors per instance
+const MAX_FDS: usize = 64;
+
+/// Most iovecs accepted by one `fd_read`/`fd_write`
+const MAX_IOVS: u32 = 1024;
+
+// Errno values
+const SUCCESS: u16 = 0;
+const EBADF: u16 = 8;
+const EEXIST: u16 = 20;
+const EFAULT: u16 = 21;
+const EINVAL: u16 = 28;
+const EIO: u16 = 29;
+const EISDIR: u16 = 31;
+const EMFILE: u16 = 33;
+const ENOENT: u16 = 44;
+const ENOSYS: u16 = 52;
+const ENOTDIR: u16 = 54;
+const ESPIPE: u16 = 70;
+const ENOTCAPABLE: u16 = 76;
+
+// File types
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+
+// `path_open` flags
+const OFLAGS_CREAT: u32 = 1;
+const OFLAGS_DIRECTORY: u32 = 2;
+const OFLAGS_EXCL: u32 = 4;
+const OFLAGS_TRUNC: u32 = 8;
+const FDFLAGS_APPEND: u32 = 1;
+
+// WASI rights bits reported by `fd_fdstat_get` and checked by `path_open`
+const RIGHT_FD_READ: u64 = 1 << 1;
+const RIGHT_FD_SEEK: u64 = 1 << 2;
+const RIGHT_FD_TELL: u64 = 1 << 5;
+const RIGHT_FD_WRITE: u64 = 1 << 6;
+const RIGHT_PATH_CREATE_FILE: u64 = 1 << 10;
+const RIGHT_PATH_OPEN: u64 = 1 << 13;
+const RIGHT_FD_READDIR: u64 = 1 << 14;
+const RIGHT_FD_FILESTAT_GET: u64 = 1 << 21;
+
+type Errno = u16;
+
+// ---------------------------------------------------------------------------
+// Grants
+// ---------------------------------------------------------------------------
+
+/// Ambient capabilities granted to a sandboxed module
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WasiCaps(u32);
+
+impl WasiCaps {
+    pub const NONE: Self = Self(0);
+    pub const STDIN: Self = Self(1 << 0);
+    pub const STDOUT: Self = Self(1 << 1);
+    pub const STDERR: Self = Self(1 << 2);
+    /// Wall and monotonic clocks
+    pub const CLOCK: Self = Self(1 << 3);
+    /// `random_get`
+    pub const RANDOM: Self = Self(1 << 4);
+
+    /// Standard output and error only
+    pub const STDIO_OUT: Self = Self(Self::STDOUT.0 | Self::STDERR.0);
+
+    /// Whether all of `other` is granted.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Parse a comma-separated list such as `stdin,clock,random`.
+    pub fn parse(list: &str) -> Option<Self> {
+        let mut caps = Self::NONE;
+        for name in list.split(',').filter(|s| !s.is_empty()) {
+            caps = caps
+                | match name {
+                    "stdin" => Self::STDIN,
+                    "stdout" => Self::STDOUT,
+                    "stderr" => Self::STDERR,
+                    "clock" => Self::CLOCK,
+                    "random" => Self::RANDOM,
+                    _ => return None,
+                };
+        }
+        Some(caps)
+    }
+}
+
+impl core::ops::BitOr for WasiCaps {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Where granted standard output goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sink {
+    Console,
+    Capture(Vec<u8>),
+}
+
+enum Fd {
+    Stdin,
+    Stdout,
+    Stderr,
+    Dir {
+        /// Name reported by `fd_prestat_dir_name`, for preopens
+        guest: Option<String>,
+        host: String,
+        rights: Rights,
+    },
+    File {
+        node: Arc<dyn VfsNode>,
+        offset: usize,
+        rights: Rights,
+        append: bool,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// WASI Context
+// ---------------------------------------------------------------------------
+
+/// Per-instance WASI state: arguments, environment, descriptors and grants
+pub struct WasiCtx {
+    args: Vec<String>,
+    env: Vec<String>,
+    caps: WasiCaps,
+    stdin: Vec<u8>,
+    stdin_pos: usize,
+    stdout: Sink,
+    fds: Vec<Option<Fd>>,
+    denials: u32,
+}
+
+impl WasiCtx {
+    /// Context with `args` (program name first) and no grants.
+    pub fn new(args: Vec<String>) -> Self {
+        Self {
+            args,
+            env: Vec::new(),
+            caps: WasiCaps::NONE,
+            stdin: Vec::new(),
+            stdin_pos: 0,
+            stdout: Sink::Console,
+            fds: alloc::vec![Some(Fd::Stdin), Some(Fd::Stdout), Some(Fd::Stderr)],
+            denials: 0,
+        }
+    }
+
+    /// Grant `caps` in addition to those already held.
+    pub fn grant(mut self, caps: WasiCaps) -> Self {
+        self.caps = self.caps | caps;
+        self
+    }
+
+    /// Pass environment variable `key=value`.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push(alloc::format!("{}={}", key, value));
+        self
+    }
+
+    /// Serve `data` on standard input (requires [`WasiCaps::STDIN`]).
+    pub fn stdin(mut self, data: Vec<u8>) -> Self {
+        self.stdin = data;
+        self.stdin_pos = 0;
+        self
+    }
+
+    /// Collect standard output and error instead of printing them.
+    pub fn capture_output(mut self) -> Self {
+        self.stdout = Sink::Capture(Vec::new());
+        self
+    }
+
+    /// Make host directory `host` visible to the module as `guest` with
+    /// `rights` (`READ`, `WRITE`, `CREATE`).
+    pub fn preopen_dir(mut self, guest: &str, host: &str, rights: Rights) -> Self {
+        if self.fds.len() < MAX_FDS {
+            self.fds.push(Some(Fd::Dir {
+                guest: Some(guest.to_string()),
+                host: host.trim_end_matches('/').to_string(),
+                rights,
+            }));
+        }
+        self
+    }
+
+    /// Number of calls refused for lack of a grant.
+    pub fn denials(&self) -> u32 {
+        self.denials
+    }
+
+    /// Take the captured output, if capturing.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        match &mut self.stdout {
+            Sink::Capture(buf) => core::mem::take(buf),
+            Sink::Console => Vec::new(),
+        }
+    }
+
+    fn deny(&mut self) -> Errno {
+        self.denials += 1;
+        ENOTCAPABLE
+    }
+
+    fn require(&mut self, caps: WasiCaps) -> Result<(), Errno> {
+        if self.caps.contains(caps) {
+            Ok(())
+        } else {
+            Err(self.deny())
+        }
+    }
+
+    fn fd(&mut self, fd: u64) -> Result<&mut Fd, Errno> {
+        self.fds
+            .get_mut(fd as u32 as usize)
+            .and_then(Option::as_mut)
+            .ok_or(EBADF)
+    }
+
+    fn alloc_fd(&mut self, entry: Fd) -> Result<u32, Errno> {
+        if let Some(i) = self.fds.iter().position(Option::is_none) {
+            self.fds[i] = Some(entry);
+            return Ok(i as u32);
+        }
+        if self.fds.len() >= MAX_FDS {
+            return Err(EMFILE);
+        }
+        self.fds.push(Some(entry));
+        Ok(self.fds.len() as u32 - 1)
+    }
+
+    fn emit(&mut self, data: &[u8]) {
+        match &mut self.stdout {
+            Sink::Console => crate::print!("{}", String::from_utf8_lossy(data)),
+            Sink::Capture(buf) => buf.extend_from_slice(data),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Import Table
+// ---------------------------------------------------------------------------
+
+/// Implemented functions: name, parameter types (`i` = i32, `I` = i64) and
+/// whether an errno is returned.
+const FUNCS: &[(&str, &str, bool)] = &[
+    ("args_get", "ii", true),
+    ("args_sizes_get", "ii", true),
+    ("environ_get", "ii", true),
+    ("environ_sizes_get", "ii", true),
+    ("clock_res_get", "ii", true),
+    ("clock_time_get", "iIi", true),
+    ("fd_close", "i", true),
+    ("fd_fdstat_get", "ii", true),
+    ("fd_filestat_get", "ii", true),
+    ("fd_prestat_get", "ii", true),
+    ("fd_prestat_dir_name", "iii", true),
+    ("fd_read", "iiii", true),
+    ("fd_seek", "iIii", true),
+    ("fd_tell", "ii", true),
+    ("fd_write", "iiii", true),
+    ("path_open", "iiiiiIIii", true),
+    ("proc_exit", "i", false),
+    ("random_get", "ii", true),
+    ("sched_yield", "", true),
+];
+
+/// Host id of the `ENOSYS` stub
+const STUB: usize = usize::MAX;
+
+fn signature_matches(sig: &str, has_result: bool, ty: &FuncType) -> bool {
+    let results_ok = if has_result {
+        ty.results == [ValType::I32]
+    } else {
+        ty.results.is_empty()
+    };
+    results_ok
+        && ty.params.len() == sig.len()
+        && sig
+            .bytes()
+            .zip(&ty.params)
+            .all(|(c, t)| matches!((c, t), (b'i', ValType::I32) | (b'I', ValType::I64)))
+}
+
+impl Host for WasiCtx {
+    fn resolve(&mut self, module: &str, name: &str, ty: &FuncType) -> Option<usize> {
+        if module != WASI_MODULE {
+            return None;
+        }
+        match FUNCS.iter().position(|(n, _, _)| *n == name) {
+            Some(id) => {
+                let (_, sig, has_result) = FUNCS[id];
+                signature_matches(sig, has_result, ty).then_some(id)
+            }
+            None => (ty.results == [ValType::I32]).then_some(STUB),
+        }
+    }
+
+    fn call(&mut self, id: usize, a: &[u64], mem: &mut Memory) -> Result<Option<u64>, Trap> {
+        let Some(&(name, _, _)) = FUNCS.get(id) else {
+            return Ok(Some(ENOSYS as u64));
+        };
+        if name == "proc_exit" {
+            return Err(Trap::Exit(a[0] as u32 as i32));
+        }
+        let errno = match self.dispatch(name, a, mem) {
+            Ok(()) => SUCCESS,
+            Err(errno) => errno,
+        };
+        Ok(Some(errno as u64))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Implementations
+// ---------------------------------------------------------------------------
+
+impl WasiCtx {
+    fn dispatch(&mut self, name: &str, a: &[u64], mem: &mut Memory) -> Result<(), Errno> {
+        let p = |i: usize| a[i] as u32;
+        match name {
+            "args_get" => write_strings(mem, &self.args, p(0), p(1)),
+            "args_sizes_get" => write_sizes(mem, &self.args, p(0), p(1)),
+            "environ_get" => write_strings(mem, &self.env, p(0), p(1)),
+            "environ_sizes_get" => write_sizes(mem, &self.env, p(0), p(1)),
+            "clock_res_get" => self
+                .require(WasiCaps::CLOCK)
+                .and_then(|_| put_u64(mem, p(1), 1_000_000)),
+            "clock_time_get" => self.require(WasiCaps::CLOCK).and_then(|_| {
+                if p(0) > 3 {
+                    return Err(EINVAL);
+                }
+                // No RTC yet: every clock counts from boot.
+                put_u64(mem, p(2), crate::timer::get_uptime_ms() * 1_000_000)
+            }),
+            "fd_close" => match self.fds.get_mut(p(0) as usize) {
+                Some(slot @ Some(_)) => {
+                    *slot = None;
+                    Ok(())
+                }
+                _ => Err(EBADF),
+            },
+            "fd_fdstat_get" => self.fd_fdstat_get(mem, a[0], p(1)),
+            "fd_filestat_get" => self.fd_filestat_get(mem, a[0], p(1)),
+            "fd_prestat_get" => match self.fd(a[0])? {
+                Fd::Dir {
+                    guest: Some(guest), ..
+                } => {
+                    let len = guest.len() as u32;
+                    put_u32(mem, p(1), 0).and_then(|_| put_u32(mem, p(1) + 4, len))
+                }
+                _ => Err(EBADF),
+            },
+            "fd_prestat_dir_name" => match self.fd(a[0])? {
+                Fd::Dir {
+                    guest: Some(guest), ..
+                } => {
+                    let n = (p(2) as usize).min(guest.len());
+                    let name = guest.as_bytes()[..n].to_vec();
+                    put_bytes(mem, p(1), &name)
+                }
+                _ => Err(EBADF),
+            },
+            "fd_read" => self.fd_read(mem, a[0], p(1), p(2), p(3)),
+            "fd_seek" => self.fd_seek(mem, a[0], a[1] as i64, p(2), p(3)),
+            "fd_tell" => match self.fd(a[0])? {
+                Fd::File { offset, .. } => {
+                    let offset = *offset as u64;
+                    put_u64(mem, p(1), offset)
+                }
+                _ => Err(ESPIPE),
+            },
+            "fd_write" => self.fd_write(mem, a[0], p(1), p(2), p(3)),
+            "path_open" => self.path_open(mem, a),
+            "random_get" => {
+                self.require(WasiCaps::RANDOM)?;
+                let buf = mem.bytes_mut(p(0), p(1)).map_err(|_| EFAULT)?;
+                let rng = crate::crypto::random::get_random();
+                // Same per-request bound as sys_getrandom.
+                for chunk in buf.chunks_mut(256) {
+                    rng.fill_bytes(chunk).map_err(|_| EIO)?;
+                }
+                Ok(())
+            }
+            "sched_yield" => Ok(()),
+            _ => Err(ENOSYS),
+        }
+    }
+
+    fn fd_fdstat_get(&mut self, mem: &mut Memory, fd: u64, out: u32) -> Result<(), Errno> {
+        let (filetype, flags, rights) = match self.fd(fd)? {
+            Fd::Stdin => (FILETYPE_CHARACTER_DEVICE, 0, RIGHT_FD_READ),
+            Fd::Stdout | Fd::Stderr => (FILETYPE_CHARACTER_DEVICE, 0, RIGHT_FD_WRITE),
+            Fd::Dir { rights, .. } => (FILETYPE_DIRECTORY, 0, wasi_rights(*rights)),
+            Fd::File { rights, append, .. } => (
+                FILETYPE_REGULAR_FILE,
+                if *append { FDFLAGS_APPEND as u16 } else { 0 },
+                wasi_rights(*rights),
+            ),
+        };
+        let mut stat = [0u8; 24];
+        stat[0] = filetype;
+        stat[2..4].copy_from_slice(&flags.to_le_bytes());
+        stat[8..16].copy_from_slice(&rights.to_le_bytes());
+        stat[16..24].copy_from_slice(&rights.to_le_bytes());
+        put_bytes(mem, out, &stat)
+    }
+
+    fn fd_filestat_get(&mut self, mem: &mut Memory, fd: u64, out: u32) -> Result<(), Errno> {
+        let mut stat = [0u8; 64];
+        match self.fd(fd)? {
+            Fd::File { node, .. } => {
+                let meta = node.metadata().map_err(|_| EIO)?;
+                stat[8..16].copy_from_slice(&meta.inode.to_le_bytes());
+                stat[16] = FILETYPE_REGULAR_FILE;
+                stat[24..32].copy_from_slice(&1u64.to_le_bytes());
+                stat[32..40].copy_from_slice(&(meta.size as u64).to_le_bytes());
+            }
+            Fd::Dir { .. } => stat[16] = FILETYPE_DIRECTORY,
+            _ => stat[16] = FILETYPE_CHARACTER_DEVICE,
+        }
+        put_bytes(mem, out, &stat)
+    }
+
+    fn fd_read(
+        &mut self,
+        mem: &mut Memory,
+        fd: u64,
+        iovs: u32,
+        count: u32,
+        nread_out: u32,
+    ) -> Result<(), Errno> {
+        let iovs = read_iovs(mem, iovs, count)?;
+        let mut total = 0u32;
+        match self.fd(fd)? {
+            Fd::Stdin => {
+                self.require(WasiCaps::STDIN)?;
+                for (ptr, len) in iovs {
+                    let rest = &self.stdin[self.stdin_pos..];
+                    let n = rest.len().min(len as usize);
+                    put_bytes(mem, ptr, &rest[..n])?;
+                    self.stdin_pos += n;
+                    total += n as u32;
+                    if n < len as usize {
+                        break;
+                    }
+                }
+            }
+            Fd::File {
+                node,
+                offset,
+                rights,
+                ..
+            } => {
+                if !rights.contains(Rights::READ) {
+                    return Err(self.deny());
+                }
+                for (ptr, len) in iovs {
+                    let buf = mem.bytes_mut(ptr, len).map_err(|_| EFAULT)?;
+                    let n = node.read(*offset, buf).map_err(|_| EIO)?;
+                    *offset += n;
+                    total += n as u32;
+                    if n < len as usize {
+                        break;
+                    }
+                }
+            }
+            Fd::Dir { .. } => return Err(EISDIR),
+            Fd::Stdout | Fd::Stderr => return Err(EBADF),
+        }
+        put_u32(mem, nread_out, total)
+    }
+
+    fn fd_write(
+        &mut self,
+        mem: &mut Memory,
+        fd: u64,
+        iovs: u32,
+        count: u32,
+        nwritten_out: u32,
+    ) -> Result<(), Errno> {
+        let iovs = read_iovs(mem, iovs, count)?;
+        let mut data = Vec::new();
+        for (ptr, len) in iovs {
+            data.extend_from_slice(mem.bytes(ptr, len).map_err(|_| EFAULT)?);
+        }
+        match self.fd(fd)? {
+            Fd::Stdout => {
+                self.require(WasiCaps::STDOUT)?;
+                self.emit(&data);
+            }
+            Fd::Stderr => {
+                self.require(WasiCaps::STDERR)?;
+                self.emit(&data);
+            }
+            Fd::File {
+                node,
+                offset,
+                rights,
+                append,
+            } => {
+                if !rights.contains(Rights::WRITE) {
+                    return Err(self.deny());
+                }
+                if *append {
+                    *offset = node.metadata().map_err(|_| EIO)?.size;
+                }
+                let n = node.write(*offset, &data).map_err(|_| EIO)?;
+                *offset += n;
+                return put_u32(mem, nwritten_out, n as u32);
+            }
+            Fd::Dir { .. } => return Err(EISDIR),
+            Fd::Stdin => return Err(EBADF),
+        }
+        put_u32(mem, nwritten_out, data.len() as u32)
+    }
+
+    fn fd_seek(
+        &mut self,
+        mem: &mut Memory,
+        fd: u64,
+        delta: i64,
+        whence: u32,
+        out: u32,
+    ) -> Result<(), Errno> {
+        let Fd::File { node, offset, .. } = self.fd(fd)? else {
+            return Err(ESPIPE);
+        };
+        let base = match whence {
+            0 => 0,
+            1 => *offset as i64,
+            2 => node.metadata().map_err(|_| EIO)?.size as i64,
+            _ => return Err(EINVAL),
+        };
+        let new = base.checked_add(delta).filter(|&n| n >= 0).ok_or(EINVAL)?;
+        *offset = new as usize;
+        put_u64(mem, out, new as u64)
+    }
+
+    /// `path_open(dirfd, dirflags, path, path_len, oflags, rights_base,
+    /// rights_inheriting, fdflags, fd_out)`
+    fn path_open(&mut self, mem: &mut Memory, a: &[u64]) -> Result<(), Errno> {
+        let path = mem.bytes(a[2] as u32, a[3] as u32).map_err(|_| EFAULT)?;
+        let path = core::str::from_utf8(path).map_err(|_| EINVAL)?.to_string();
+        let oflags = a[4] as u32;
+        let rights_base = a[5];
+        let fdflags = a[7] as u32;
+
+        let (dir_host, dir_rights) = match self.fd(a[0])? {
+            Fd::Dir { host, rights, .. } => (host.clone(), *rights),
+            _ => return Err(ENOTDIR),
+        };
+        let Some(relative) = confine(&path) else {
+            return Err(self.deny());
+        };
+        let host_path = if relative.is_empty() {
+            dir_host.clone()
+        } else {
+            alloc::format!("{}/{}", dir_host, relative)
+        };
+
+        let append = fdflags & FDFLAGS_APPEND != 0;
+        let wants_write = rights_base & RIGHT_FD_WRITE != 0 || oflags & OFLAGS_TRUNC != 0 || append;
+        let mut needed = Rights::READ;
+        if wants_write {
+            needed = Rights::WRITE;
+        }
+        if oflags & OFLAGS_CREAT != 0 {
+            needed = Rights::WRITE.union(Rights::CREATE);
+        }
+        if !dir_rights.contains(needed) {
+            return Err(self.deny());
+        }
+
+        let vfs = crate::fs::get_vfs().read();
+        let node = match vfs.resolve_path(&host_path) {
+            Ok(_) if oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0 => return Err(EEXIST),
+            Ok(node) => node,
+            Err(_) if oflags & OFLAGS_CREAT != 0 => {
+                let (parent, name) = host_path.rsplit_once('/').ok_or(EINVAL)?;
+                let parent = if parent.is_empty() { "/" } else { parent };
+                vfs.resolve_path(parent)
+                    .and_then(|dir| dir.create(name, Permissions::default()))
+                    .map_err(|_| EIO)?
+            }
+            Err(_) => return Err(ENOENT),
+        };
+        drop(vfs);
+
+        let is_dir = node.node_type() == NodeType::Directory;
+        if oflags & OFLAGS_DIRECTORY != 0 && !is_dir {
+            return Err(ENOTDIR);
+        }
+        let entry = if is_dir {
+            if wants_write {
+                return Err(EISDIR);
+            }
+            Fd::Dir {
+                guest: None,
+                host: host_path,
+                rights: dir_rights,
+            }
+        } else {
+            if oflags & OFLAGS_TRUNC != 0 {
+                node.truncate(0).map_err(|_| EIO)?;
+            }
+            let mut rights = Rights::new(0);
+            if rights_base & RIGHT_FD_READ != 0 || !wants_write {
+                rights = Rights::READ;
+            }
+            if wants_write {
+                rights = rights.union(Rights::WRITE);
+            }
+            Fd::File {
+                node,
+                offset: 0,
+                rights,
+                append,
+            }
+        };
+        let fd = self.alloc_fd(entry)?;
+        put_u32(mem, a[8] as u32, fd)
+    }
+}
+
+/// Resolve `path` inside a preopen: relative only, `.` dropped, `..` may
+/// not climb above the preopen. Returns the normalized relative path.
+fn confine(path: &str) -> Option<String> {
+    if path.starts_with('/') {
+        return None;
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            p => parts.push(p),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// WASI rights bits corresponding to capability rights.
+fn wasi_rights(rights: Rights) -> u64 {
+    let mut bits = RIGHT_FD_SEEK | RIGHT_FD_TELL | RIGHT_FD_FILESTAT_GET;
+    if rights.contains(Rights::READ) {
+        bits |= RIGHT_FD_READ | RIGHT_FD_READDIR | RIGHT_PATH_OPEN;
+    }
+    if rights.contains(Rights::WRITE) {
+        bits |= RIGHT_FD_WRITE;
+    }
+    if rights.contains(Rights::CREATE) {
+        bits |= RIGHT_PATH_CREATE_FILE;
+    }
+    bits
+}
+
+fn read_iovs(mem: &Memory, iovs: u32, count: u32) -> Result<Vec<(u32, u32)>, Errno> {
+    if count > MAX_IOVS {
+        return Err(EINVAL);
+    }
+    (0..count)
+        .map(|i| {
+            let at = iovs.checked_add(i * 8).ok_or(EFAULT)?;
+            let ptr = mem.read_u32(at).map_err(|_| EFAULT)?;
+            let len = mem.read_u32(at.wrapping_add(4)).map_err(|_| EFAULT)?;
+            Ok((ptr, len))
+        })
+        .collect()
+}
+
+fn put_bytes(mem: &mut Memory, addr: u32, data: &[u8]) -> Result<(), Errno> {
+    mem.bytes_mut(addr, data.len() as u32)
+        .map_err(|_| EFAULT)?
+        .copy_from_slice(data);
+    Ok(())
+}
+
+fn put_u32(mem: &mut Memory, addr: u32, value: u32) -> Result<(), Errno> {
+    mem.write_u32(addr, value).map_err(|_| EFAULT)
+}
+
+fn put_u64(mem: &mut Memory, addr: u32, value: u64) -> Result<(), Errno> {
+    mem.write_u64(addr, value).map_err(|_| EFAULT)
+}
+
+/// `args_get`/`environ_get`: pointer array at `ptrs`, NUL-terminated
+/// strings packed at `buf`.
+fn write_strings(mem: &mut Memory, list: &[String], ptrs: u32, buf: u32) -> Result<(), Errno> {
+    let mut at = buf;
+    for (i, s) in list.iter().enumerate() {
+        put_u32(mem, ptrs.wrapping_add(i as u32 * 4), at)?;
+        put_bytes(mem, at, s.as_bytes())?;
+        put_bytes(mem, at.wrapping_add(s.len() as u32), &[0])?;
+        at = at.wrapping_add(s.len() as u32 + 1);
+    }
+    Ok(())
+}
+
+/// `args_sizes_get`/`environ_sizes_get`: count and total buffer size.
+fn write_sizes(mem: &mut Memory, list: &[String], count: u32, size: u32) -> Result<(), Errno> {
+    let total: usize = list.iter().map(|s| s.len() + 1).sum();
+    put_u32(mem, count, list.len() as u32)?;
+    put_u32(mem, size, total as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confine() {
+        assert_eq!(confine("a/./b"), Some("a/b".to_string()));
+        assert_eq!(confine("a/../b"), Some("b".to_string()));
+        assert_eq!(confine("."), Some(String::new()));
+        assert_eq!(confine("../etc/passwd"), None);
+        assert_eq!(confine("a/../../b"), None);
+        assert_eq!(confine("/etc/passwd"), None);
+    }
+
+    #[test]
+    fn test_caps_parse() {
+        let caps = WasiCaps::parse("stdin,clock").unwrap();
+        assert!(caps.contains(WasiCaps::STDIN));
+        assert!(caps.contains(WasiCaps::CLOCK));
+        assert!(!caps.contains(WasiCaps::RANDOM));
+        assert_eq!(WasiCaps::parse(""), Some(WasiCaps::NONE));
+        assert_eq!(WasiCaps::parse("network"), None);
+    }
+
+    #[test]
+    fn test_resolve_signatures() {
+        let mut ctx = WasiCtx::new(Vec::new());
+        let fd_write = FuncType {
+            params: alloc::vec![ValType::I32; 4],
+            results: alloc::vec![ValType::I32],
+        };
+        assert!(ctx.resolve(WASI_MODULE, "fd_write", &fd_write).is_some());
+        assert!(ctx.resolve("env", "fd_write", &fd_write).is_none());
+        // Wrong signature for a known function
+        let bad = FuncType {
+            params: alloc::vec![ValType::I64],
+            results: alloc::vec![ValType::I32],
+        };
+        assert!(ctx.resolve(WASI_MODULE, "fd_close", &bad).is_none());
+        // Unknown WASI functions link to the ENOSYS stub
+        assert_eq!(ctx.resolve(WASI_MODULE, "sock_accept", &bad), Some(STUB));
+    }
+
+    #[test]
+    fn test_stdout_denied_without_grant() {
+        let fd_write = FUNCS.iter().position(|f| f.0 == "fd_write").unwrap();
+        let mut mem = Memory::default();
+        // Zero iovecs, so no guest memory is touched.
+        let mut ctx = WasiCtx::new(Vec::new()).capture_output();
+        let r = ctx.call(fd_write, &[1, 0, 0, 0], &mut mem).unwrap();
+        assert_eq!(r, Some(ENOTCAPABLE as u64));
+        assert_eq!(ctx.denials(), 1);
+
+        // Granted: the nwritten store faults on the empty memory instead.
+        let mut ctx = ctx.grant(WasiCaps::STDOUT);
+        let r = ctx.call(fd_write, &[1, 0, 0, 0], &mut mem).unwrap();
+        assert_eq!(r, Some(EFAULT as u64));
+        assert_eq!(ctx.denials(), 1);
+    }
+}
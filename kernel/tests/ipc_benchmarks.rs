@@ -252,6 +252,32 @@ fn bench_fast_path_vs_slow_path() {
     serial_println!("[ok]");
 }
 
+// ===== Regression Gate =====
+
+#[test_case]
+fn bench_ipc_suite_regressions() {
+    use veridian_kernel::bench::ipc::{check, run, IpcBenchConfig, BASELINES};
+
+    ipc::init();
+    let results = run(&IpcBenchConfig::default()).expect("IPC benchmark suite failed");
+    for result in &results {
+        serial_println!("{}", result.record());
+    }
+
+    let regressions = check(&results, BASELINES);
+    for r in &regressions {
+        serial_println!(
+            "  REGRESSION {}: {} = {} (limit {})",
+            r.name,
+            r.metric,
+            r.measured,
+            r.limit
+        );
+    }
+    assert!(regressions.is_empty(), "IPC performance regressed");
+    serial_println!("[ok]");
+}
+
 // ===== Performance Statistics =====
 
 #[test_case]
@@ -0,0 +1,982 @@
+//! BlockFS on-disk format shared by the kernel driver and the host tools.
+//!
+//! The kernel's BlockFS driver, `mkfs-blockfs` and the `blockfs-fuzz` image
+//! checker all read and write images through this crate, so the layout is
+//! defined exactly once:
+//!
+//! ```text
+//! Block 0:              Superblock (76 bytes serialized, padded to 4KB)
+//! Blocks 1..1+B:        Block bitmap (B = ceil(total_blocks / 32768))
+//! Blocks 1+B..1+B+I:    Inode table (I = ceil(inode_count * S / 4096))
+//! Blocks 1+B+I..end:    Data blocks
+//! ```
+//!
+//! Every field is little-endian. Directory data is a sequence of ext2-style
+//! variable-length records that never cross a block boundary; each inode may
+//! own one extended attribute block.
+//!
+//! # Versions
+//!
+//! Version 1 images have 96-byte inodes (S = 96) with 32-bit sizes and
+//! 32-bit second timestamps; their superblock ends after 62 bytes. Version 2
+//! adds a version number and ext2-style feature words to the superblock and
+//! sets [`FEATURE_INCOMPAT_WIDE_INODES`]: 128-byte inodes (S = 128) with
+//! 64-bit sizes, 64-bit nanosecond timestamps and a creation time. A v1
+//! superblock reads as version 1 with no features, so v1 images stay
+//! mountable; [`Superblock::inode_format`] says how to read the inode table.
+
+#![no_std]
+
+use core::fmt;
+
+/// Block size (4KB)
+pub const BLOCK_SIZE: usize = 4096;
+
+/// Magic number for BlockFS
+pub const BLOCKFS_MAGIC: u32 = 0x424C4B46; // "BLKF"
+
+/// On-disk superblock lives in block 0
+pub const SUPERBLOCK_BLOCK: u32 = 0;
+
+/// Serialized superblock size in bytes
+pub const SUPERBLOCK_SIZE: usize = 76;
+
+/// Superblock size of version 1 images, which lack the version and feature
+/// fields
+pub const SUPERBLOCK_SIZE_V1: usize = 62;
+
+/// Format version of images without a version field
+pub const FORMAT_V1: u16 = 1;
+
+/// Format version that introduced the version and feature fields
+pub const FORMAT_V2: u16 = 2;
+
+/// Version written by this crate
+pub const FORMAT_VERSION: u16 = FORMAT_V2;
+
+/// Incompatible feature: 128-byte inodes with 64-bit sizes and nanosecond
+/// timestamps
+pub const FEATURE_INCOMPAT_WIDE_INODES: u32 = 1 << 0;
+
+/// Compatible features this crate knows (others are ignored)
+pub const SUPPORTED_COMPAT: u32 = 0;
+
+/// Read-only compatible features this crate can write (others make the
+/// image read-only)
+pub const SUPPORTED_RO_COMPAT: u32 = 0;
+
+/// Incompatible features this crate can read (others refuse the image)
+pub const SUPPORTED_INCOMPAT: u32 = FEATURE_INCOMPAT_WIDE_INODES;
+
+/// Serialized DiskInode size in bytes (version 2)
+pub const DISK_INODE_SIZE: usize = 128;
+
+/// Serialized DiskInode size of version 1 images
+pub const DISK_INODE_SIZE_V1: usize = 96;
+
+/// Number of DiskInodes that fit in one block (version 2)
+pub const INODES_PER_BLOCK: usize = BLOCK_SIZE / DISK_INODE_SIZE; // 32
+
+/// Nanoseconds per second, the unit conversion between v1 and v2
+/// timestamps
+pub const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Inode number of the root directory
+pub const ROOT_INODE: u32 = 0;
+
+/// Number of direct block pointers in a DiskInode
+pub const DIRECT_BLOCKS: usize = 12;
+
+/// Number of block pointers that fit in one indirect block (4096 / 4 = 1024)
+pub const PTRS_PER_BLOCK: usize = BLOCK_SIZE / 4;
+
+/// Maximum file size addressable via direct blocks only: 12 * 4KB = 48KB
+pub const DIRECT_MAX_BLOCKS: usize = DIRECT_BLOCKS;
+
+/// Maximum file size addressable via direct + single indirect:
+/// 12 + 1024 = 1036 blocks = ~4MB
+pub const SINGLE_INDIRECT_MAX_BLOCKS: usize = DIRECT_BLOCKS + PTRS_PER_BLOCK;
+
+/// Maximum file size addressable via direct + single + double indirect:
+/// 12 + 1024 + 1024*1024 = 1_049_612 blocks = ~4GB
+pub const DOUBLE_INDIRECT_MAX_BLOCKS: usize =
+    DIRECT_BLOCKS + PTRS_PER_BLOCK + PTRS_PER_BLOCK * PTRS_PER_BLOCK;
+
+/// Mask of the file type bits of `DiskInode::mode`
+pub const S_IFMT: u16 = 0xF000;
+/// File type bits of a directory
+pub const S_IFDIR: u16 = 0x4000;
+/// File type bits of a regular file
+pub const S_IFREG: u16 = 0x8000;
+/// File type bits of a symbolic link
+pub const S_IFLNK: u16 = 0xA000;
+
+/// Maximum filename length
+pub const MAX_FILENAME_LEN: usize = 255;
+
+/// Size of the fixed header of a directory entry (inode + rec_len + name_len
+/// + file_type)
+pub const DIR_ENTRY_HEADER_SIZE: usize = 8;
+
+/// Directory entry file type of a regular file
+pub const FT_REG_FILE: u8 = 1;
+/// Directory entry file type of a directory
+pub const FT_DIR: u8 = 2;
+/// Directory entry file type of a symbolic link
+pub const FT_SYMLINK: u8 = 7;
+
+/// Magic number at the start of an inode's extended attribute block
+pub const XATTR_BLOCK_MAGIC: u32 = 0x42544158; // "XATB"
+
+/// Size of the xattr block header (magic + entry count + reserved)
+pub const XATTR_BLOCK_HEADER_SIZE: usize = 8;
+
+/// Size of an xattr entry header (name_len u8, reserved u8, value_len u16)
+pub const XATTR_ENTRY_HEADER_SIZE: usize = 4;
+
+/// Why a structure could not be encoded or decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatError {
+    /// The structure runs past the end of its buffer.
+    Truncated,
+    /// The magic number does not match.
+    BadMagic,
+    /// The data does not fit in one block.
+    NoSpace,
+    /// The image uses a version or incompatible feature this crate cannot
+    /// read.
+    Unsupported,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => f.write_str("structure runs past the end of its block"),
+            Self::BadMagic => f.write_str("bad magic number"),
+            Self::NoSpace => f.write_str("data does not fit in one block"),
+            Self::Unsupported => f.write_str("unsupported format version or feature"),
+        }
+    }
+}
+
+/// Round `val` up to the next 4-byte boundary.
+pub const fn align4(val: usize) -> usize {
+    (val + 3) & !3
+}
+
+/// Number of blocks needed for the block bitmap.
+/// Each byte covers 8 blocks, each block is 4096 bytes = 32768 bits.
+pub const fn bitmap_blocks(total_blocks: u32) -> u32 {
+    let bytes_needed = (total_blocks as usize).div_ceil(8);
+    bytes_needed.div_ceil(BLOCK_SIZE) as u32
+}
+
+/// Inode table layout of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InodeFormat {
+    /// 96-byte inodes: 32-bit size, 32-bit second timestamps (version 1)
+    V1,
+    /// 128-byte inodes: 64-bit size, 64-bit nanosecond timestamps and a
+    /// creation time (version 2)
+    V2,
+}
+
+impl InodeFormat {
+    /// Serialized size of one inode.
+    pub const fn inode_size(self) -> usize {
+        match self {
+            Self::V1 => DISK_INODE_SIZE_V1,
+            Self::V2 => DISK_INODE_SIZE,
+        }
+    }
+
+    /// Number of inodes that fit in one block.
+    pub const fn inodes_per_block(self) -> usize {
+        BLOCK_SIZE / self.inode_size()
+    }
+
+    /// Largest file size the inode can record.
+    pub const fn max_file_size(self) -> u64 {
+        match self {
+            Self::V1 => u32::MAX as u64,
+            Self::V2 => u64::MAX,
+        }
+    }
+}
+
+/// Number of blocks needed for the inode table.
+pub const fn inode_table_blocks(inode_count: u32, format: InodeFormat) -> u32 {
+    (inode_count as usize).div_ceil(format.inodes_per_block()) as u32
+}
+
+/// First block of the inode table.
+pub const fn inode_table_start(total_blocks: u32) -> u32 {
+    SUPERBLOCK_BLOCK + 1 + bitmap_blocks(total_blocks)
+}
+
+/// First data block for a filesystem of `total_blocks` blocks and
+/// `inode_count` inodes.
+pub const fn computed_first_data_block(
+    total_blocks: u32,
+    inode_count: u32,
+    format: InodeFormat,
+) -> u32 {
+    inode_table_start(total_blocks) + inode_table_blocks(inode_count, format)
+}
+
+/// Block holding `inode` and the byte offset of the inode within it.
+pub const fn inode_location(total_blocks: u32, inode: u32, format: InodeFormat) -> (u32, usize) {
+    let index = inode as usize;
+    let per_block = format.inodes_per_block();
+    let block = inode_table_start(total_blocks) + (index / per_block) as u32;
+    (block, (index % per_block) * format.inode_size())
+}
+
+fn u16_at(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn u32_at(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn u64_at(buf: &[u8], off: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[off..off + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Superblock structure
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Superblock {
+    pub magic: u32,
+    pub block_count: u32,
+    pub inode_count: u32,
+    pub free_blocks: u32,
+    pub free_inodes: u32,
+    pub first_data_block: u32,
+    pub block_size: u32,
+    pub inode_size: u16,
+    pub blocks_per_group: u32,
+    pub inodes_per_group: u32,
+    pub mount_time: u64,
+    pub write_time: u64,
+    pub mount_count: u16,
+    pub max_mount_count: u16,
+    pub state: u16,
+    pub errors: u16,
+    /// Format version ([`FORMAT_V1`] for images that predate the field)
+    pub version: u16,
+    /// Features that older drivers may safely ignore
+    pub feature_compat: u32,
+    /// Features that older drivers may read but must not write
+    pub feature_ro_compat: u32,
+    /// Features that older drivers cannot read at all
+    pub feature_incompat: u32,
+}
+
+impl Superblock {
+    /// A clean superblock for a fresh filesystem of the current version with
+    /// only the root inode in use.
+    pub fn new(block_count: u32, inode_count: u32) -> Self {
+        Self::with_format(block_count, inode_count, InodeFormat::V2)
+    }
+
+    /// Like [`Superblock::new`], but for the given inode format. `V1`
+    /// produces a version 1 superblock without features.
+    pub fn with_format(block_count: u32, inode_count: u32, format: InodeFormat) -> Self {
+        let first_data = computed_first_data_block(block_count, inode_count, format);
+        let (version, feature_incompat) = match format {
+            InodeFormat::V1 => (FORMAT_V1, 0),
+            InodeFormat::V2 => (FORMAT_VERSION, FEATURE_INCOMPAT_WIDE_INODES),
+        };
+        Self {
+            magic: BLOCKFS_MAGIC,
+            block_count,
+            inode_count,
+            free_blocks: block_count.saturating_sub(first_data),
+            free_inodes: inode_count - 1, // Reserve root inode
+            first_data_block: first_data,
+            block_size: BLOCK_SIZE as u32,
+            inode_size: format.inode_size() as u16,
+            blocks_per_group: 8192,
+            inodes_per_group: 2048,
+            mount_time: 0,
+            write_time: 0,
+            mount_count: 0,
+            max_mount_count: 100,
+            state: 1, // Clean
+            errors: 0,
+            version,
+            feature_compat: 0,
+            feature_ro_compat: 0,
+            feature_incompat,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.magic == BLOCKFS_MAGIC
+    }
+
+    /// How to read the inode table, or [`FormatError::Unsupported`] if the
+    /// image needs an incompatible feature this crate does not know or its
+    /// inode size does not match its features.
+    pub fn inode_format(&self) -> Result<InodeFormat, FormatError> {
+        if self.feature_incompat & !SUPPORTED_INCOMPAT != 0 {
+            return Err(FormatError::Unsupported);
+        }
+        let format = if self.feature_incompat & FEATURE_INCOMPAT_WIDE_INODES != 0 {
+            InodeFormat::V2
+        } else {
+            InodeFormat::V1
+        };
+        if self.inode_size as usize != format.inode_size() {
+            return Err(FormatError::Unsupported);
+        }
+        Ok(format)
+    }
+
+    /// Whether the image may be modified: false if it uses a read-only
+    /// compatible feature this crate does not know.
+    pub fn is_writable(&self) -> bool {
+        self.feature_ro_compat & !SUPPORTED_RO_COMPAT == 0
+    }
+
+    /// Serialize into the first [`SUPERBLOCK_SIZE`] bytes of `buf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than [`SUPERBLOCK_SIZE`].
+    pub fn encode(&self, buf: &mut [u8]) {
+        buf[0..4].copy_from_slice(&self.magic.to_le_bytes());
+        buf[4..8].copy_from_slice(&self.block_count.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.inode_count.to_le_bytes());
+        buf[12..16].copy_from_slice(&self.free_blocks.to_le_bytes());
+        buf[16..20].copy_from_slice(&self.free_inodes.to_le_bytes());
+        buf[20..24].copy_from_slice(&self.first_data_block.to_le_bytes());
+        buf[24..28].copy_from_slice(&self.block_size.to_le_bytes());
+        buf[28..30].copy_from_slice(&self.inode_size.to_le_bytes());
+        buf[30..34].copy_from_slice(&self.blocks_per_group.to_le_bytes());
+        buf[34..38].copy_from_slice(&self.inodes_per_group.to_le_bytes());
+        buf[38..46].copy_from_slice(&self.mount_time.to_le_bytes());
+        buf[46..54].copy_from_slice(&self.write_time.to_le_bytes());
+        buf[54..56].copy_from_slice(&self.mount_count.to_le_bytes());
+        buf[56..58].copy_from_slice(&self.max_mount_count.to_le_bytes());
+        buf[58..60].copy_from_slice(&self.state.to_le_bytes());
+        buf[60..62].copy_from_slice(&self.errors.to_le_bytes());
+        buf[62..64].copy_from_slice(&self.version.to_le_bytes());
+        buf[64..68].copy_from_slice(&self.feature_compat.to_le_bytes());
+        buf[68..72].copy_from_slice(&self.feature_ro_compat.to_le_bytes());
+        buf[72..76].copy_from_slice(&self.feature_incompat.to_le_bytes());
+    }
+
+    /// Parse a superblock. The magic number is not checked; see
+    /// [`Superblock::is_valid`]. A v1 superblock, whose version field is the
+    /// zero padding after it, reads as [`FORMAT_V1`] without features.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than [`SUPERBLOCK_SIZE`].
+    pub fn decode(buf: &[u8]) -> Self {
+        Self {
+            magic: u32_at(buf, 0),
+            block_count: u32_at(buf, 4),
+            inode_count: u32_at(buf, 8),
+            free_blocks: u32_at(buf, 12),
+            free_inodes: u32_at(buf, 16),
+            first_data_block: u32_at(buf, 20),
+            block_size: u32_at(buf, 24),
+            inode_size: u16_at(buf, 28),
+            blocks_per_group: u32_at(buf, 30),
+            inodes_per_group: u32_at(buf, 34),
+            mount_time: u64_at(buf, 38),
+            write_time: u64_at(buf, 46),
+            mount_count: u16_at(buf, 54),
+            max_mount_count: u16_at(buf, 56),
+            state: u16_at(buf, 58),
+            errors: u16_at(buf, 60),
+            version: u16_at(buf, 62).max(FORMAT_V1),
+            feature_compat: u32_at(buf, 64),
+            feature_ro_compat: u32_at(buf, 68),
+            feature_incompat: u32_at(buf, 72),
+        }
+    }
+}
+
+/// On-disk inode structure
+///
+/// The fields follow the version 2 layout. Timestamps are nanoseconds since
+/// the Unix epoch in both versions; a v1 inode stores whole seconds, so
+/// sub-second precision and the creation time are lost there.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskInode {
+    pub mode: u16,
+    pub uid: u16,
+    pub gid: u16,
+    pub links_count: u16,
+    pub flags: u32,
+    pub blocks: u32,
+    pub size: u64,
+    pub atime: u64,
+    pub ctime: u64,
+    pub mtime: u64,
+    pub dtime: u64,
+    /// Creation time (0 = unknown, as in every v1 inode)
+    pub crtime: u64,
+    pub direct_blocks: [u32; DIRECT_BLOCKS],
+    pub indirect_block: u32,
+    pub double_indirect_block: u32,
+    /// Block holding the inode's extended attributes (0 = none)
+    pub xattr_block: u32,
+}
+
+impl DiskInode {
+    /// A new inode with one link and no data. Unused inode table slots are
+    /// all zeros instead; see [`DiskInode::default`].
+    pub fn new(mode: u16, uid: u16, gid: u16) -> Self {
+        Self {
+            mode,
+            uid,
+            gid,
+            links_count: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        (self.mode & S_IFDIR) != 0
+    }
+
+    pub fn is_file(&self) -> bool {
+        (self.mode & S_IFREG) != 0
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        (self.mode & S_IFLNK) == S_IFLNK
+    }
+
+    /// Serialize into the first `format.inode_size()` bytes of `buf`.
+    ///
+    /// A v1 inode keeps the low 32 bits of the size (callers keep files
+    /// within [`InodeFormat::max_file_size`]) and whole seconds, saturated
+    /// at `u32::MAX`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than the inode.
+    pub fn encode(&self, buf: &mut [u8], format: InodeFormat) {
+        match format {
+            InodeFormat::V1 => self.encode_v1(buf),
+            InodeFormat::V2 => self.encode_v2(buf),
+        }
+    }
+
+    fn encode_v1(&self, buf: &mut [u8]) {
+        let secs = |ns: u64| (ns / NANOS_PER_SEC).min(u32::MAX as u64) as u32;
+        buf[0..2].copy_from_slice(&self.mode.to_le_bytes());
+        buf[2..4].copy_from_slice(&self.uid.to_le_bytes());
+        buf[4..8].copy_from_slice(&(self.size as u32).to_le_bytes());
+        buf[8..12].copy_from_slice(&secs(self.atime).to_le_bytes());
+        buf[12..16].copy_from_slice(&secs(self.ctime).to_le_bytes());
+        buf[16..20].copy_from_slice(&secs(self.mtime).to_le_bytes());
+        buf[20..24].copy_from_slice(&secs(self.dtime).to_le_bytes());
+        buf[24..26].copy_from_slice(&self.gid.to_le_bytes());
+        buf[26..28].copy_from_slice(&self.links_count.to_le_bytes());
+        buf[28..32].copy_from_slice(&self.blocks.to_le_bytes());
+        buf[32..36].copy_from_slice(&self.flags.to_le_bytes());
+        self.encode_pointers(&mut buf[36..DISK_INODE_SIZE_V1]);
+    }
+
+    fn encode_v2(&self, buf: &mut [u8]) {
+        buf[0..2].copy_from_slice(&self.mode.to_le_bytes());
+        buf[2..4].copy_from_slice(&self.uid.to_le_bytes());
+        buf[4..6].copy_from_slice(&self.gid.to_le_bytes());
+        buf[6..8].copy_from_slice(&self.links_count.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.flags.to_le_bytes());
+        buf[12..16].copy_from_slice(&self.blocks.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.size.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.atime.to_le_bytes());
+        buf[32..40].copy_from_slice(&self.ctime.to_le_bytes());
+        buf[40..48].copy_from_slice(&self.mtime.to_le_bytes());
+        buf[48..56].copy_from_slice(&self.dtime.to_le_bytes());
+        buf[56..64].copy_from_slice(&self.crtime.to_le_bytes());
+        self.encode_pointers(&mut buf[64..124]);
+        buf[124..128].fill(0); // Reserved
+    }
+
+    /// Direct, indirect, double indirect and xattr block pointers (60 bytes),
+    /// laid out the same way in both versions.
+    fn encode_pointers(&self, buf: &mut [u8]) {
+        for (j, &blk) in self.direct_blocks.iter().enumerate() {
+            buf[j * 4..j * 4 + 4].copy_from_slice(&blk.to_le_bytes());
+        }
+        buf[48..52].copy_from_slice(&self.indirect_block.to_le_bytes());
+        buf[52..56].copy_from_slice(&self.double_indirect_block.to_le_bytes());
+        buf[56..60].copy_from_slice(&self.xattr_block.to_le_bytes());
+    }
+
+    /// Parse an inode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than `format.inode_size()`.
+    pub fn decode(buf: &[u8], format: InodeFormat) -> Self {
+        match format {
+            InodeFormat::V1 => {
+                let ns = |off| u32_at(buf, off) as u64 * NANOS_PER_SEC;
+                Self {
+                    mode: u16_at(buf, 0),
+                    uid: u16_at(buf, 2),
+                    size: u32_at(buf, 4) as u64,
+                    atime: ns(8),
+                    ctime: ns(12),
+                    mtime: ns(16),
+                    dtime: ns(20),
+                    gid: u16_at(buf, 24),
+                    links_count: u16_at(buf, 26),
+                    blocks: u32_at(buf, 28),
+                    flags: u32_at(buf, 32),
+                    crtime: 0,
+                    ..Self::decode_pointers(&buf[36..DISK_INODE_SIZE_V1])
+                }
+            }
+            InodeFormat::V2 => Self {
+                mode: u16_at(buf, 0),
+                uid: u16_at(buf, 2),
+                gid: u16_at(buf, 4),
+                links_count: u16_at(buf, 6),
+                flags: u32_at(buf, 8),
+                blocks: u32_at(buf, 12),
+                size: u64_at(buf, 16),
+                atime: u64_at(buf, 24),
+                ctime: u64_at(buf, 32),
+                mtime: u64_at(buf, 40),
+                dtime: u64_at(buf, 48),
+                crtime: u64_at(buf, 56),
+                ..Self::decode_pointers(&buf[64..124])
+            },
+        }
+    }
+
+    /// An inode holding only the block pointers of `buf` (see
+    /// [`DiskInode::encode_pointers`]).
+    fn decode_pointers(buf: &[u8]) -> Self {
+        let mut direct_blocks = [0u32; DIRECT_BLOCKS];
+        for (j, block) in direct_blocks.iter_mut().enumerate() {
+            *block = u32_at(buf, j * 4);
+        }
+        Self {
+            direct_blocks,
+            indirect_block: u32_at(buf, 48),
+            double_indirect_block: u32_at(buf, 52),
+            xattr_block: u32_at(buf, 56),
+            ..Self::default()
+        }
+    }
+}
+
+/// Record length of a directory entry with a `name_len` byte name.
+pub const fn dir_entry_len(name_len: usize) -> usize {
+    align4(DIR_ENTRY_HEADER_SIZE + name_len)
+}
+
+/// Fixed header of an on-disk directory entry (ext2-style variable-length
+/// record)
+///
+/// Layout:
+///   - inode:     4 bytes (inode number, 0 = deleted entry)
+///   - rec_len:   2 bytes (total record length, always 4-byte aligned)
+///   - name_len:  1 byte  (actual name length)
+///   - file_type: 1 byte  (`FT_*`)
+///   - name:      `name_len` bytes, zero-padded to `rec_len`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirEntryHeader {
+    pub inode: u32,
+    pub rec_len: u16,
+    pub name_len: u8,
+    pub file_type: u8,
+}
+
+impl DirEntryHeader {
+    /// Parse the header at `offset`, or `None` if it runs past the end of
+    /// `block`. The record itself is not validated.
+    pub fn decode(block: &[u8], offset: usize) -> Option<Self> {
+        let header = block.get(offset..offset.checked_add(DIR_ENTRY_HEADER_SIZE)?)?;
+        Some(Self {
+            inode: u32_at(header, 0),
+            rec_len: u16_at(header, 4),
+            name_len: header[6],
+            file_type: header[7],
+        })
+    }
+
+    /// Whether `rec_len` can chain to the next record: at least a header
+    /// long and 4-byte aligned. Directory walks stop at the first record
+    /// that fails this.
+    pub fn rec_len_ok(&self) -> bool {
+        self.rec_len as usize >= DIR_ENTRY_HEADER_SIZE && self.rec_len.is_multiple_of(4)
+    }
+
+    /// The name bytes of the entry at `offset`, clipped to the end of
+    /// `block`.
+    pub fn name<'a>(&self, block: &'a [u8], offset: usize) -> &'a [u8] {
+        let start = (offset + DIR_ENTRY_HEADER_SIZE).min(block.len());
+        let end = (start + self.name_len as usize).min(block.len());
+        &block[start..end]
+    }
+}
+
+/// Write a directory entry at `offset`: the header, the name (truncated to
+/// [`MAX_FILENAME_LEN`]) and zero padding up to its record length, which is
+/// returned.
+///
+/// # Panics
+///
+/// Panics if the record runs past the end of `block`.
+pub fn encode_dir_entry(
+    block: &mut [u8],
+    offset: usize,
+    inode: u32,
+    name: &[u8],
+    file_type: u8,
+) -> usize {
+    let name_len = name.len().min(MAX_FILENAME_LEN);
+    let rec_len = dir_entry_len(name_len);
+    let record = &mut block[offset..offset + rec_len];
+    record[0..4].copy_from_slice(&inode.to_le_bytes());
+    record[4..6].copy_from_slice(&(rec_len as u16).to_le_bytes());
+    record[6] = name_len as u8;
+    record[7] = file_type;
+    record[DIR_ENTRY_HEADER_SIZE..DIR_ENTRY_HEADER_SIZE + name_len]
+        .copy_from_slice(&name[..name_len]);
+    record[DIR_ENTRY_HEADER_SIZE + name_len..].fill(0);
+    rec_len
+}
+
+/// Encode attributes as a full xattr block into `block`.
+///
+/// Layout: magic (u32), entry count (u16), reserved (u16), then per entry
+/// name length (u8), reserved (u8), value length (u16), the name and the
+/// value, padded to a 4-byte boundary.
+///
+/// Fails with [`FormatError::NoSpace`] if the attributes do not fit in
+/// `block`.
+pub fn encode_xattr_block<'a, I>(attrs: I, block: &mut [u8]) -> Result<(), FormatError>
+where
+    I: ExactSizeIterator<Item = (&'a [u8], &'a [u8])>,
+{
+    if block.len() < XATTR_BLOCK_HEADER_SIZE || attrs.len() > u16::MAX as usize {
+        return Err(FormatError::NoSpace);
+    }
+    block.fill(0);
+    block[0..4].copy_from_slice(&XATTR_BLOCK_MAGIC.to_le_bytes());
+    block[4..6].copy_from_slice(&(attrs.len() as u16).to_le_bytes());
+
+    let mut pos = XATTR_BLOCK_HEADER_SIZE;
+    for (name, value) in attrs {
+        let end = pos + XATTR_ENTRY_HEADER_SIZE + name.len() + value.len();
+        if end > block.len() || name.len() > u8::MAX as usize {
+            return Err(FormatError::NoSpace);
+        }
+        block[pos] = name.len() as u8;
+        block[pos + 2..pos + 4].copy_from_slice(&(value.len() as u16).to_le_bytes());
+        let name_start = pos + XATTR_ENTRY_HEADER_SIZE;
+        block[name_start..name_start + name.len()].copy_from_slice(name);
+        block[name_start + name.len()..end].copy_from_slice(value);
+        pos = align4(end);
+    }
+    Ok(())
+}
+
+/// Iterate over the `(name, value)` pairs of an xattr block (see
+/// [`encode_xattr_block`]).
+///
+/// Fails up front if the header is bad; entries that overrun the block are
+/// reported as [`FormatError::Truncated`], after which iteration stops.
+pub fn decode_xattr_block(block: &[u8]) -> Result<XattrEntries<'_>, FormatError> {
+    if block.len() < XATTR_BLOCK_HEADER_SIZE {
+        return Err(FormatError::Truncated);
+    }
+    if u32_at(block, 0) != XATTR_BLOCK_MAGIC {
+        return Err(FormatError::BadMagic);
+    }
+    Ok(XattrEntries {
+        block,
+        pos: XATTR_BLOCK_HEADER_SIZE,
+        remaining: u16_at(block, 4),
+    })
+}
+
+/// Iterator returned by [`decode_xattr_block`].
+pub struct XattrEntries<'a> {
+    block: &'a [u8],
+    pos: usize,
+    remaining: u16,
+}
+
+impl<'a> Iterator for XattrEntries<'a> {
+    type Item = Result<(&'a [u8], &'a [u8]), FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let pos = self.pos;
+        if pos + XATTR_ENTRY_HEADER_SIZE > self.block.len() {
+            self.remaining = 0;
+            return Some(Err(FormatError::Truncated));
+        }
+        let name_len = self.block[pos] as usize;
+        let value_len = u16_at(self.block, pos + 2) as usize;
+        let name_start = pos + XATTR_ENTRY_HEADER_SIZE;
+        let value_start = name_start + name_len;
+        let end = value_start + value_len;
+        if end > self.block.len() {
+            self.remaining = 0;
+            return Some(Err(FormatError::Truncated));
+        }
+        self.pos = align4(end);
+        Some(Ok((
+            &self.block[name_start..value_start],
+            &self.block[value_start..end],
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_math() {
+        use InodeFormat::{V1, V2};
+        assert_eq!(INODES_PER_BLOCK, 32);
+        assert_eq!(V1.inodes_per_block(), 42);
+        assert_eq!(bitmap_blocks(1), 1);
+        assert_eq!(bitmap_blocks(32768), 1);
+        assert_eq!(bitmap_blocks(32769), 2);
+        assert_eq!(inode_table_blocks(42, V1), 1);
+        assert_eq!(inode_table_blocks(43, V1), 2);
+        assert_eq!(inode_table_blocks(32, V2), 1);
+        assert_eq!(inode_table_blocks(33, V2), 2);
+        assert_eq!(computed_first_data_block(10000, 1000, V1), 1 + 1 + 24);
+        assert_eq!(computed_first_data_block(10000, 1000, V2), 1 + 1 + 32);
+        assert_eq!(inode_location(10000, 0, V2), (2, 0));
+        assert_eq!(inode_location(10000, 43, V1), (3, DISK_INODE_SIZE_V1));
+        assert_eq!(inode_location(10000, 33, V2), (3, DISK_INODE_SIZE));
+        assert_eq!(core::mem::size_of::<DiskInode>(), DISK_INODE_SIZE);
+    }
+
+    #[test]
+    fn test_superblock_round_trip() {
+        let mut sb = Superblock::new(10000, 1000);
+        sb.mount_time = 0x0102_0304_0506_0708;
+        sb.write_time = u64::MAX - 1;
+        sb.errors = 3;
+        let mut buf = [0u8; BLOCK_SIZE];
+        sb.encode(&mut buf);
+        assert!(buf[SUPERBLOCK_SIZE..].iter().all(|&b| b == 0));
+        assert_eq!(&buf[0..4], b"FKLB");
+
+        let decoded = Superblock::decode(&buf);
+        assert_eq!(decoded, sb);
+        assert!(decoded.is_valid());
+        assert!(!Superblock::decode(&[0u8; SUPERBLOCK_SIZE]).is_valid());
+        assert_eq!(decoded.version, FORMAT_V2);
+        assert_eq!(decoded.inode_format(), Ok(InodeFormat::V2));
+        assert!(decoded.is_writable());
+    }
+
+    #[test]
+    fn test_superblock_v1_compat() {
+        // A v1 superblock is zero past its 62 bytes.
+        let sb = Superblock::with_format(10000, 1000, InodeFormat::V1);
+        let mut buf = [0u8; BLOCK_SIZE];
+        sb.encode(&mut buf);
+        buf[62..64].fill(0);
+        assert!(buf[SUPERBLOCK_SIZE_V1..].iter().all(|&b| b == 0));
+
+        let decoded = Superblock::decode(&buf);
+        assert_eq!(decoded.version, FORMAT_V1);
+        assert_eq!(decoded.inode_size as usize, DISK_INODE_SIZE_V1);
+        assert_eq!(decoded.inode_format(), Ok(InodeFormat::V1));
+        assert_eq!(decoded.first_data_block, 1 + 1 + 24);
+    }
+
+    #[test]
+    fn test_superblock_features() {
+        let mut sb = Superblock::new(10000, 1000);
+        sb.feature_compat = 1 << 31;
+        assert_eq!(sb.inode_format(), Ok(InodeFormat::V2));
+        assert!(sb.is_writable());
+
+        sb.feature_ro_compat = 1 << 31;
+        assert_eq!(sb.inode_format(), Ok(InodeFormat::V2));
+        assert!(!sb.is_writable());
+
+        sb.feature_incompat |= 1 << 31;
+        assert_eq!(sb.inode_format(), Err(FormatError::Unsupported));
+
+        // Inode size must agree with the features.
+        let mut sb = Superblock::new(10000, 1000);
+        sb.inode_size = DISK_INODE_SIZE_V1 as u16;
+        assert_eq!(sb.inode_format(), Err(FormatError::Unsupported));
+    }
+
+    #[test]
+    fn test_disk_inode_round_trip() {
+        let mut inode = DiskInode::new(S_IFREG | 0o644, 1000, 100);
+        inode.size = 5 << 32;
+        inode.mtime = 4_400_000_000 * NANOS_PER_SEC + 123;
+        inode.crtime = 17;
+        inode.blocks = 31;
+        for (i, block) in inode.direct_blocks.iter_mut().enumerate() {
+            *block = 100 + i as u32;
+        }
+        inode.indirect_block = 200;
+        inode.double_indirect_block = 300;
+        inode.xattr_block = 400;
+
+        let mut buf = [0xAAu8; DISK_INODE_SIZE];
+        inode.encode(&mut buf, InodeFormat::V2);
+        assert_eq!(DiskInode::decode(&buf, InodeFormat::V2), inode);
+        assert!(inode.is_file() && !inode.is_dir() && !inode.is_symlink());
+
+        let mut zeros = [0xAAu8; DISK_INODE_SIZE];
+        DiskInode::default().encode(&mut zeros, InodeFormat::V2);
+        assert!(zeros.iter().all(|&b| b == 0));
+        assert!(DiskInode::new(S_IFLNK | 0o777, 0, 0).is_symlink());
+    }
+
+    #[test]
+    fn test_disk_inode_v1() {
+        let mut inode = DiskInode::new(S_IFDIR | 0o755, 1000, 100);
+        inode.size = 123_456;
+        inode.links_count = 2;
+        inode.atime = 42 * NANOS_PER_SEC + 999;
+        inode.mtime = u64::MAX;
+        inode.crtime = 17;
+        inode.direct_blocks[11] = 111;
+        inode.xattr_block = 400;
+
+        let mut buf = [0xAAu8; DISK_INODE_SIZE];
+        inode.encode(&mut buf, InodeFormat::V1);
+        assert!(buf[DISK_INODE_SIZE_V1..].iter().all(|&b| b == 0xAA));
+        assert_eq!(u32_at(&buf, 4), 123_456);
+        assert_eq!(u16_at(&buf, 26), 2);
+        assert_eq!(u32_at(&buf, 36 + 11 * 4), 111);
+
+        // Sub-second precision and the creation time do not survive; times
+        // past 2106 saturate.
+        let decoded = DiskInode::decode(&buf, InodeFormat::V1);
+        assert_eq!(decoded.atime, 42 * NANOS_PER_SEC);
+        assert_eq!(decoded.mtime, u32::MAX as u64 * NANOS_PER_SEC);
+        assert_eq!(decoded.crtime, 0);
+        assert_eq!(
+            decoded,
+            DiskInode {
+                atime: decoded.atime,
+                mtime: decoded.mtime,
+                crtime: 0,
+                ..inode
+            }
+        );
+    }
+
+    #[test]
+    fn test_dir_entry_round_trip() {
+        let mut block = [0xFFu8; BLOCK_SIZE];
+        let first = encode_dir_entry(&mut block, 0, 7, b"hello", FT_REG_FILE);
+        assert_eq!(first, 16);
+        assert_eq!(&block[13..16], &[0, 0, 0]);
+        let second = encode_dir_entry(&mut block, first, 8, b"sub", FT_DIR);
+
+        let header = DirEntryHeader::decode(&block, 0).unwrap();
+        assert_eq!(
+            header,
+            DirEntryHeader {
+                inode: 7,
+                rec_len: 16,
+                name_len: 5,
+                file_type: FT_REG_FILE,
+            }
+        );
+        assert!(header.rec_len_ok());
+        assert_eq!(header.name(&block, 0), b"hello");
+
+        let header = DirEntryHeader::decode(&block, first).unwrap();
+        assert_eq!(header.rec_len as usize, second);
+        assert_eq!(header.name(&block, first), b"sub");
+        assert_eq!(header.file_type, FT_DIR);
+
+        assert!(DirEntryHeader::decode(&block, BLOCK_SIZE - 4).is_none());
+        assert!(DirEntryHeader::decode(&block, usize::MAX).is_none());
+        let long = [b'x'; 300];
+        assert_eq!(
+            encode_dir_entry(&mut block, 0, 1, &long, FT_REG_FILE),
+            dir_entry_len(MAX_FILENAME_LEN)
+        );
+    }
+
+    #[test]
+    fn test_dir_entry_bad_rec_len() {
+        let mut block = [0u8; BLOCK_SIZE];
+        assert!(!DirEntryHeader::decode(&block, 0).unwrap().rec_len_ok());
+        block[4] = 10;
+        assert!(!DirEntryHeader::decode(&block, 0).unwrap().rec_len_ok());
+        // A name length past the block end is clipped, not a panic.
+        block[BLOCK_SIZE - 2] = 200;
+        let header = DirEntryHeader::decode(&block, BLOCK_SIZE - 8).unwrap();
+        assert!(header.name(&block, BLOCK_SIZE - 8).is_empty());
+    }
+
+    #[test]
+    fn test_xattr_round_trip() {
+        let attrs: [(&[u8], &[u8]); 3] = [
+            (b"security.label", b"system_u"),
+            (b"user.color", b"red"),
+            (b"user.empty", b""),
+        ];
+        let mut block = [0xAAu8; BLOCK_SIZE];
+        encode_xattr_block(attrs.iter().copied(), &mut block).unwrap();
+
+        let mut decoded = decode_xattr_block(&block).unwrap();
+        for expected in attrs {
+            assert_eq!(decoded.next(), Some(Ok(expected)));
+        }
+        assert_eq!(decoded.next(), None);
+        assert_eq!(
+            decode_xattr_block(&[0u8; BLOCK_SIZE]).err(),
+            Some(FormatError::BadMagic)
+        );
+        assert_eq!(
+            decode_xattr_block(&block[..4]).err(),
+            Some(FormatError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_xattr_limits() {
+        let value = [1u8; BLOCK_SIZE];
+        let mut block = [0u8; BLOCK_SIZE];
+        let too_big = [(&b"user.big"[..], &value[..])];
+        assert_eq!(
+            encode_xattr_block(too_big.iter().copied(), &mut block),
+            Err(FormatError::NoSpace)
+        );
+
+        // An entry whose lengths overrun the block ends iteration.
+        let fits = [(&b"user.a"[..], &value[..16])];
+        encode_xattr_block(fits.iter().copied(), &mut block).unwrap();
+        block[XATTR_BLOCK_HEADER_SIZE + 2..XATTR_BLOCK_HEADER_SIZE + 4]
+            .copy_from_slice(&u16::MAX.to_le_bytes());
+        block[4] = 2;
+        let mut entries = decode_xattr_block(&block).unwrap();
+        assert_eq!(entries.next(), Some(Err(FormatError::Truncated)));
+        assert_eq!(entries.next(), None);
+    }
+}
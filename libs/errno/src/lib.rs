@@ -0,0 +1,327 @@
+//! VeridianOS error numbers.
+//!
+//! System calls return a non-negative value on success and `-errno` on
+//! failure. The numbering is VeridianOS's own (`ENOENT` is 4, not Linux's
+//! 2) and is published to C programs by `<veridian/errno.h>`; this crate is
+//! the Rust side of that header, used by the kernel to encode syscall
+//! errors and by userland to decode them and print `strerror`-style
+//! messages. The tests check every code and message against the header and
+//! libc's `strerror`, so the three cannot drift apart.
+//!
+//! The crate is `no_std` and has no dependencies.
+
+#![no_std]
+
+use core::fmt;
+
+/// An error number as returned (negated) by a system call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Errno(i32);
+
+macro_rules! errno_codes {
+    ($($(#[$doc:meta])* $name:ident = $code:literal => $msg:literal,)*) => {
+        impl Errno {
+            $($(#[$doc])* pub const $name: Errno = Errno($code);)*
+
+            /// Every error number with a name, in ascending order.
+            pub const ALL: &'static [Errno] = &[$(Errno::$name),*];
+
+            /// The C macro name of this error number, e.g. `"ENOENT"`.
+            pub const fn name(self) -> Option<&'static str> {
+                match self.0 {
+                    $($code => Some(stringify!($name)),)*
+                    _ => None,
+                }
+            }
+
+            /// The message `strerror` prints for this error number.
+            pub const fn message(self) -> &'static str {
+                match self.0 {
+                    $($code => $msg,)*
+                    _ => "Unknown error",
+                }
+            }
+        }
+    };
+}
+
+errno_codes! {
+    /// Invalid or unimplemented system call.
+    ENOSYS = 1 => "Function not implemented",
+    /// Invalid argument.
+    EINVAL = 2 => "Invalid argument",
+    /// Operation not permitted.
+    EPERM = 3 => "Operation not permitted",
+    /// No such file, process or endpoint.
+    ENOENT = 4 => "No such file or directory",
+    /// Out of memory.
+    ENOMEM = 5 => "Cannot allocate memory",
+    /// The operation would block.
+    EAGAIN = 6 => "Resource temporarily unavailable",
+    /// Interrupted by a signal.
+    EINTR = 7 => "Interrupted system call",
+    /// The object is not in a state that allows the operation.
+    EILSEQ = 8 => "Invalid state",
+    /// Bad user pointer.
+    EFAULT = 9 => "Bad address",
+    /// Invalid capability token.
+    ECAPINVAL = 10 => "Invalid capability",
+    /// The capability has been revoked.
+    ECAPREVOKED = 11 => "Capability revoked",
+    /// The capability lacks the required rights.
+    ECAPRIGHTS = 12 => "Insufficient capability rights",
+    /// No such capability.
+    ECAPNOTFOUND = 13 => "Capability not found",
+    /// The capability already exists.
+    ECAPEXISTS = 14 => "Capability already exists",
+    /// The capability names an invalid object.
+    ECAPOBJECT = 15 => "Invalid capability object",
+    /// The capability may not be delegated.
+    ECAPDELEG = 16 => "Capability delegation denied",
+    /// The address is not mapped.
+    ENOMAPPING = 17 => "Address not mapped",
+    /// Permission denied.
+    EACCES = 18 => "Permission denied",
+    /// No such process.
+    ESRCH = 19 => "No such process",
+    /// File exists.
+    EEXIST = 20 => "File exists",
+    /// Bad file descriptor.
+    EBADF = 21 => "Bad file descriptor",
+    /// I/O error.
+    EIO = 22 => "Input/output error",
+    /// No such device or address.
+    ENXIO = 23 => "No such device or address",
+    /// Argument list too long.
+    E2BIG = 24 => "Argument list too long",
+    /// Exec format error.
+    ENOEXEC = 25 => "Exec format error",
+    /// No child processes.
+    ECHILD = 26 => "No child processes",
+    /// Device or resource busy.
+    EBUSY = 27 => "Device or resource busy",
+    /// Not a directory.
+    ENOTDIR = 28 => "Not a directory",
+    /// Is a directory.
+    EISDIR = 29 => "Is a directory",
+    /// Too many open files in the process.
+    EMFILE = 30 => "Too many open files",
+    /// Too many open files in the system.
+    ENFILE = 31 => "Too many open files in system",
+    /// Inappropriate ioctl for device.
+    ENOTTY = 32 => "Inappropriate ioctl for device",
+    /// Text file busy.
+    ETXTBSY = 33 => "Text file busy",
+    /// File too large.
+    EFBIG = 34 => "File too large",
+    /// No space left on device.
+    ENOSPC = 35 => "No space left on device",
+    /// Illegal seek.
+    ESPIPE = 36 => "Illegal seek",
+    /// Read-only file system.
+    EROFS = 37 => "Read-only file system",
+    /// Too many links.
+    EMLINK = 38 => "Too many links",
+    /// Broken pipe.
+    EPIPE = 39 => "Broken pipe",
+    /// Math argument out of domain.
+    EDOM = 40 => "Numerical argument out of domain",
+    /// Result out of range, or a buffer too small for it.
+    ERANGE = 41 => "Numerical result out of range",
+    /// Resource deadlock would occur.
+    EDEADLK = 42 => "Resource deadlock avoided",
+    /// File name too long.
+    ENAMETOOLONG = 43 => "File name too long",
+    /// No record locks available.
+    ENOLCK = 44 => "No locks available",
+    /// Directory not empty.
+    ENOTEMPTY = 45 => "Directory not empty",
+    /// Too many levels of symbolic links.
+    ELOOP = 46 => "Too many levels of symbolic links",
+    /// No message of the desired type.
+    ENOMSG = 47 => "No message of desired type",
+    /// Cross-device link.
+    EXDEV = 48 => "Invalid cross-device link",
+    /// Connection refused.
+    ECONNREFUSED = 49 => "Connection refused",
+    /// Connection reset by peer.
+    ECONNRESET = 50 => "Connection reset by peer",
+    /// No buffer space available.
+    ENOBUFS = 51 => "No buffer space available",
+    /// Protocol not supported.
+    EPROTONOSUPPORT = 52 => "Protocol not supported",
+    /// Operation not supported.
+    ENOTSUP = 53 => "Operation not supported",
+    /// Address already in use.
+    EADDRINUSE = 54 => "Address already in use",
+    /// Address not available.
+    EADDRNOTAVAIL = 55 => "Cannot assign requested address",
+    /// Network is unreachable.
+    ENETUNREACH = 56 => "Network is unreachable",
+    /// Connection timed out.
+    ETIMEDOUT = 57 => "Connection timed out",
+    /// Operation already in progress.
+    EALREADY = 58 => "Operation already in progress",
+    /// Operation now in progress.
+    EINPROGRESS = 59 => "Operation now in progress",
+    /// Socket operation on non-socket.
+    ENOTSOCK = 60 => "Socket operation on non-socket",
+    /// Destination address required.
+    EDESTADDRREQ = 61 => "Destination address required",
+    /// Message too long.
+    EMSGSIZE = 62 => "Message too long",
+    /// Protocol wrong type for socket.
+    EPROTOTYPE = 63 => "Protocol wrong type for socket",
+    /// Transport endpoint is not connected.
+    ENOTCONN = 64 => "Transport endpoint is not connected",
+    /// Transport endpoint is already connected.
+    EISCONN = 65 => "Transport endpoint is already connected",
+    /// Address family not supported.
+    EAFNOSUPPORT = 66 => "Address family not supported by protocol",
+    /// Connection aborted.
+    ECONNABORTED = 67 => "Software caused connection abort",
+    /// No route to host.
+    EHOSTUNREACH = 68 => "No route to host",
+    /// Network is down.
+    ENETDOWN = 69 => "Network is down",
+    /// Network dropped connection on reset.
+    ENETRESET = 70 => "Network dropped connection on reset",
+    /// Protocol not available.
+    ENOPROTOOPT = 71 => "Protocol not available",
+    /// No such device.
+    ENODEV = 72 => "No such device",
+    /// Value too large for defined data type.
+    EOVERFLOW = 73 => "Value too large for defined data type",
+    /// Protocol error.
+    EPROTO = 74 => "Protocol error",
+    /// Operation canceled.
+    ECANCELED = 75 => "Operation canceled",
+    /// Owner of a robust mutex died.
+    EOWNERDEAD = 76 => "Owner died",
+    /// State not recoverable.
+    ENOTRECOVERABLE = 77 => "State not recoverable",
+    /// Link has been severed.
+    ENOLINK = 78 => "Link has been severed",
+    /// A per-process or system-wide limit was reached.
+    ERESOURCELIMIT = 79 => "Resource limit exceeded",
+    /// No data available, e.g. a missing extended attribute.
+    ENODATA = 80 => "No data available",
+}
+
+impl Errno {
+    /// Same as [`Errno::EAGAIN`].
+    pub const EWOULDBLOCK: Errno = Errno::EAGAIN;
+    /// Same as [`Errno::ENOTSUP`].
+    pub const EOPNOTSUPP: Errno = Errno::ENOTSUP;
+    /// Same as [`Errno::EDEADLK`].
+    pub const EDEADLOCK: Errno = Errno::EDEADLK;
+    /// Same as [`Errno::ENODATA`].
+    pub const ENOATTR: Errno = Errno::ENODATA;
+
+    /// The error number `code`, which need not have a name.
+    pub const fn new(code: i32) -> Errno {
+        Errno(code)
+    }
+
+    /// The positive error number.
+    pub const fn code(self) -> i32 {
+        self.0
+    }
+
+    /// The value a system call returns for this error.
+    pub const fn to_ret(self) -> isize {
+        -(self.0 as isize)
+    }
+
+    /// Split a raw system call return value into its result or error.
+    pub const fn from_ret(ret: isize) -> Result<usize, Errno> {
+        if ret < 0 {
+            Err(Errno(-ret as i32))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl fmt::Display for Errno {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{string::String, vec::Vec};
+
+    use super::*;
+
+    /// `#define NAME value` lines of an errno header with a numeric value.
+    fn header_codes(header: &str) -> Vec<(String, i32)> {
+        header
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.strip_prefix("#define ")?.split_whitespace();
+                let name = words.next()?;
+                let code = words.next()?.parse().ok()?;
+                Some((String::from(name), code))
+            })
+            .collect()
+    }
+
+    fn assert_matches_header(header: &str) {
+        let codes = header_codes(header);
+        for (name, code) in &codes {
+            assert_eq!(Errno::new(*code).name(), Some(name.as_str()), "{}", name);
+        }
+        for errno in Errno::ALL {
+            assert!(
+                codes.iter().any(|(_, code)| *code == errno.code()),
+                "{:?} missing from header",
+                errno.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_codes_match_headers() {
+        assert_matches_header(include_str!(
+            "../../../userland/libc/include/veridian/errno.h"
+        ));
+        assert_matches_header(include_str!(
+            "../../../toolchain/sysroot/include/veridian/errno.h"
+        ));
+    }
+
+    #[test]
+    fn test_messages_match_libc_strerror() {
+        let source = include_str!("../../../userland/libc/src/string.c");
+        let mut seen = 0;
+        for line in source.lines() {
+            let Some(case) = line.trim().strip_prefix("case E") else {
+                continue;
+            };
+            let (name, rest) = case.split_once(':').unwrap();
+            let message = rest.split('"').nth(1).unwrap();
+            let errno = Errno::ALL
+                .iter()
+                .find(|e| e.name().unwrap()[1..] == *name.trim())
+                .unwrap();
+            assert_eq!(errno.message(), message);
+            seen += 1;
+        }
+        assert_eq!(seen, Errno::ALL.len());
+    }
+
+    #[test]
+    fn test_return_values() {
+        assert_eq!(Errno::ENOENT.to_ret(), -4);
+        assert_eq!(Errno::from_ret(-46), Err(Errno::ELOOP));
+        assert_eq!(Errno::from_ret(7), Ok(7));
+        assert_eq!(Errno::new(999).name(), None);
+        assert_eq!(Errno::new(999).message(), "Unknown error");
+        assert!(Errno::ALL.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,579 @@
+//! gettext message catalogs.
+//!
+//! A [`Catalog`] maps the English source strings of a program (`msgid`s)
+//! to their translations for one language. It loads from either
+//!
+//! - a GNU `.mo` file, as written by `msgfmt`, in either byte order; the hash
+//!   table is ignored and lookups go through a sorted map, or
+//! - a `.po` source file, so translators can drop a catalog in place without a
+//!   build step. Entries marked `#, fuzzy` and obsolete `#~` entries are
+//!   skipped, as `msgfmt` does.
+//!
+//! Messages may carry a context (`msgctxt`) to tell apart identical source
+//! strings, and a plural form (`msgid_plural` / `msgstr[N]`) selected by
+//! the catalog's `Plural-Forms` header. Lookups that miss fall back to the
+//! source string, so an empty catalog is the untranslated program.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
+
+use crate::PluralRule;
+
+/// `.mo` magic number, as read in the file's own byte order.
+const MO_MAGIC: u32 = 0x9504_12DE;
+
+/// Separates the context from the `msgid` in catalog keys.
+const CONTEXT_SEPARATOR: char = '\u{4}';
+
+/// Errors from loading a catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogError {
+    /// A `.mo` file is too short or a string runs past its end.
+    Truncated,
+    /// Not a `.mo` file.
+    BadMagic,
+    /// A `.mo` file with a major revision other than 0 or 1.
+    UnsupportedRevision,
+    /// A message is not valid UTF-8.
+    NotUtf8,
+    /// A `.po` file line that could not be parsed (1-based).
+    Syntax { line: usize },
+    /// The `Plural-Forms` header could not be parsed.
+    InvalidPluralForms,
+}
+
+impl CatalogError {
+    /// Human-readable description.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatalogError::Truncated => "truncated message catalog",
+            CatalogError::BadMagic => "not a message catalog",
+            CatalogError::UnsupportedRevision => "unsupported message catalog revision",
+            CatalogError::NotUtf8 => "message catalog is not UTF-8",
+            CatalogError::Syntax { .. } => "malformed .po entry",
+            CatalogError::InvalidPluralForms => "invalid Plural-Forms header",
+        }
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Syntax { line } => write!(f, "line {}: {}", line, self.as_str()),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+/// The translations for one language.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    /// `msgid`, or `msgctxt` + U+0004 + `msgid`, to the translation and
+    /// its plural forms.
+    messages: BTreeMap<String, Vec<String>>,
+    plural: PluralRule,
+}
+
+impl Catalog {
+    /// A catalog with no translations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a compiled `.mo` file.
+    pub fn from_mo(data: &[u8]) -> Result<Self, CatalogError> {
+        let word = |offset: usize, big_endian: bool| -> Result<u32, CatalogError> {
+            let bytes: [u8; 4] = data
+                .get(offset..offset + 4)
+                .and_then(|b| b.try_into().ok())
+                .ok_or(CatalogError::Truncated)?;
+            Ok(if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            })
+        };
+        let big_endian = match word(0, false)? {
+            MO_MAGIC => false,
+            magic if magic.swap_bytes() == MO_MAGIC => true,
+            _ => return Err(CatalogError::BadMagic),
+        };
+        if word(4, big_endian)? >> 16 > 1 {
+            return Err(CatalogError::UnsupportedRevision);
+        }
+        let count = word(8, big_endian)? as usize;
+        // Reject absurd counts before looping over them
+        if count > data.len() / 8 {
+            return Err(CatalogError::Truncated);
+        }
+        let originals = word(12, big_endian)? as usize;
+        let translations = word(16, big_endian)? as usize;
+
+        // Each string is a (length, offset) pair; the NUL after it is not
+        // counted but must be present
+        let string = |table: usize, index: usize| -> Result<&str, CatalogError> {
+            let entry = table
+                .checked_add(index * 8)
+                .ok_or(CatalogError::Truncated)?;
+            let len = word(entry, big_endian)? as usize;
+            let offset = word(entry + 4, big_endian)? as usize;
+            let end = offset.checked_add(len).ok_or(CatalogError::Truncated)?;
+            if data.get(end) != Some(&0) {
+                return Err(CatalogError::Truncated);
+            }
+            core::str::from_utf8(&data[offset..end]).map_err(|_| CatalogError::NotUtf8)
+        };
+
+        let mut catalog = Self::new();
+        for index in 0..count {
+            let original = string(originals, index)?;
+            let translation = string(translations, index)?;
+            // A plural msgid is "singular\0plural"; look it up by the first
+            let key = original.split('\0').next().unwrap_or_default();
+            catalog.insert(key, translation.split('\0').map(String::from).collect())?;
+        }
+        Ok(catalog)
+    }
+
+    /// Load a `.po` source file.
+    pub fn from_po(text: &str) -> Result<Self, CatalogError> {
+        let mut catalog = Self::new();
+        let mut entry = PoEntry::default();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            let syntax = CatalogError::Syntax { line: index + 1 };
+
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(flags) = comment.strip_prefix(',') {
+                    if entry.has_msgstr() {
+                        entry.finish(&mut catalog)?;
+                    }
+                    if flags.split(',').any(|flag| flag.trim() == "fuzzy") {
+                        entry.fuzzy = true;
+                    }
+                }
+                continue;
+            }
+            if line.starts_with('"') {
+                let continued = entry.current.ok_or(syntax)?;
+                let value = unquote(line).ok_or(syntax)?;
+                entry.field(continued).push_str(&value);
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).ok_or(syntax)?;
+            let value = unquote(rest.trim()).ok_or(syntax)?;
+            let field = match keyword {
+                "msgctxt" => Field::Context,
+                "msgid" => Field::Id,
+                "msgid_plural" => Field::IdPlural,
+                "msgstr" => Field::Str(0),
+                _ => {
+                    let n = keyword
+                        .strip_prefix("msgstr[")
+                        .and_then(|k| k.strip_suffix(']'))
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|&n| n < 16)
+                        .ok_or(syntax)?;
+                    Field::Str(n)
+                }
+            };
+            // msgctxt or msgid after a msgstr starts the next entry
+            if matches!(field, Field::Context | Field::Id) && entry.has_msgstr() {
+                entry.finish(&mut catalog)?;
+            }
+            if entry.current.is_some_and(|current| current >= field) && !entry.has_msgstr() {
+                return Err(syntax);
+            }
+            entry.current = Some(field);
+            *entry.field(field) = value;
+        }
+        entry.finish(&mut catalog)?;
+        Ok(catalog)
+    }
+
+    /// Add a message; the empty `msgid` is the header, which carries the
+    /// plural rule.
+    fn insert(&mut self, key: &str, forms: Vec<String>) -> Result<(), CatalogError> {
+        if key.is_empty() {
+            let header = forms.first().map(String::as_str).unwrap_or_default();
+            for field in header.lines() {
+                if let Some(value) = field.strip_prefix("Plural-Forms:") {
+                    self.plural = PluralRule::parse(value)?;
+                }
+            }
+            return Ok(());
+        }
+        // Untranslated entries fall back to the source string
+        if forms.iter().all(String::is_empty) {
+            return Ok(());
+        }
+        self.messages.insert(String::from(key), forms);
+        Ok(())
+    }
+
+    /// Number of translated messages.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether the catalog translates nothing.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// The catalog's plural rule.
+    pub fn plural_rule(&self) -> &PluralRule {
+        &self.plural
+    }
+
+    fn lookup(&self, context: Option<&str>, msgid: &str) -> Option<&[String]> {
+        let forms = match context {
+            Some(context) => {
+                let mut key = String::with_capacity(context.len() + 1 + msgid.len());
+                key.push_str(context);
+                key.push(CONTEXT_SEPARATOR);
+                key.push_str(msgid);
+                self.messages.get(&key)
+            }
+            None => self.messages.get(msgid),
+        };
+        forms.map(Vec::as_slice)
+    }
+
+    /// The translation of `msgid`, or `msgid` itself.
+    pub fn gettext<'a>(&'a self, msgid: &'a str) -> &'a str {
+        self.pgettext_opt(None, msgid)
+    }
+
+    /// The translation of `msgid` in `context`, or `msgid` itself.
+    pub fn pgettext<'a>(&'a self, context: &str, msgid: &'a str) -> &'a str {
+        self.pgettext_opt(Some(context), msgid)
+    }
+
+    /// The form of a plural message for count `n`. Untranslated, `singular`
+    /// is used for 1 and `plural` otherwise.
+    pub fn ngettext<'a>(&'a self, singular: &'a str, plural: &'a str, n: u64) -> &'a str {
+        self.npgettext_opt(None, singular, plural, n)
+    }
+
+    /// [`Catalog::ngettext`] with a context.
+    pub fn npgettext<'a>(
+        &'a self,
+        context: &str,
+        singular: &'a str,
+        plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        self.npgettext_opt(Some(context), singular, plural, n)
+    }
+
+    fn pgettext_opt<'a>(&'a self, context: Option<&str>, msgid: &'a str) -> &'a str {
+        match self.lookup(context, msgid).and_then(|forms| forms.first()) {
+            Some(translation) if !translation.is_empty() => translation,
+            _ => msgid,
+        }
+    }
+
+    fn npgettext_opt<'a>(
+        &'a self,
+        context: Option<&str>,
+        singular: &'a str,
+        plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        let form = self
+            .lookup(context, singular)
+            .and_then(|forms| forms.get(self.plural.index(n)));
+        match form {
+            Some(translation) if !translation.is_empty() => translation,
+            _ if n == 1 => singular,
+            _ => plural,
+        }
+    }
+}
+
+/// The parts of a `.po` entry, in the order they must appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+/// A `.po` entry being read.
+#[derive(Debug, Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: Option<String>,
+    /// Parsed but unused: lookups go by the singular.
+    id_plural: String,
+    strs: Vec<String>,
+    fuzzy: bool,
+    /// The field continuation lines append to.
+    current: Option<Field>,
+}
+
+impl PoEntry {
+    fn has_msgstr(&self) -> bool {
+        matches!(self.current, Some(Field::Str(_)))
+    }
+
+    fn field(&mut self, field: Field) -> &mut String {
+        match field {
+            Field::Context => self.context.get_or_insert_with(String::new),
+            Field::Id => self.id.get_or_insert_with(String::new),
+            Field::IdPlural => &mut self.id_plural,
+            Field::Str(n) => {
+                if self.strs.len() <= n {
+                    self.strs.resize(n + 1, String::new());
+                }
+                &mut self.strs[n]
+            }
+        }
+    }
+
+    /// Add the entry to `catalog` unless it is fuzzy, and start afresh.
+    fn finish(&mut self, catalog: &mut Catalog) -> Result<(), CatalogError> {
+        let entry = core::mem::take(self);
+        let Some(id) = entry.id else {
+            return Ok(());
+        };
+        // A fuzzy header still says how to form plurals
+        if entry.fuzzy && !id.is_empty() {
+            return Ok(());
+        }
+        match entry.context {
+            Some(context) => {
+                let mut key = context;
+                key.push(CONTEXT_SEPARATOR);
+                key.push_str(&id);
+                catalog.insert(&key, entry.strs)
+            }
+            None => catalog.insert(&id, entry.strs),
+        }
+    }
+}
+
+/// The contents of a C-style quoted string, with escapes resolved.
+fn unquote(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => out.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                'a' => '\u{7}',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'v' => '\u{b}',
+                '\\' => '\\',
+                '"' => '"',
+                '\'' => '\'',
+                '?' => '?',
+                _ => return None,
+            }),
+            _ => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+
+    const PO: &str = r#"
+# German translations
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+#: terminal.rs:10
+msgid "command not found"
+msgstr "Befehl nicht gefunden"
+
+msgid ""
+"multi"
+"line"
+msgstr "mehr\tzeilig \"zitiert\""
+
+msgctxt "menu"
+msgid "Open"
+msgstr "Öffnen"
+
+msgid "Open"
+msgstr "Offen"
+
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d Datei"
+msgstr[1] "%d Dateien"
+
+#, fuzzy, c-format
+msgid "Save"
+msgstr "Sichern"
+
+msgid "Untranslated"
+msgstr ""
+
+#~ msgid "Old"
+#~ msgstr "Alt"
+"#;
+
+    #[test]
+    fn test_po_lookups() {
+        let catalog = Catalog::from_po(PO).unwrap();
+        assert_eq!(catalog.len(), 5);
+        assert_eq!(
+            catalog.gettext("command not found"),
+            "Befehl nicht gefunden"
+        );
+        assert_eq!(catalog.gettext("multiline"), "mehr\tzeilig \"zitiert\"");
+        assert_eq!(catalog.pgettext("menu", "Open"), "Öffnen");
+        assert_eq!(catalog.gettext("Open"), "Offen");
+        assert_eq!(catalog.pgettext("toolbar", "Open"), "Open");
+        assert_eq!(catalog.ngettext("%d file", "%d files", 1), "%d Datei");
+        assert_eq!(catalog.ngettext("%d file", "%d files", 3), "%d Dateien");
+        // Fuzzy, empty, obsolete and unknown messages are untranslated
+        assert_eq!(catalog.gettext("Save"), "Save");
+        assert_eq!(catalog.gettext("Untranslated"), "Untranslated");
+        assert_eq!(catalog.gettext("Old"), "Old");
+        assert_eq!(catalog.ngettext("%d dir", "%d dirs", 1), "%d dir");
+        assert_eq!(catalog.ngettext("%d dir", "%d dirs", 0), "%d dirs");
+    }
+
+    #[test]
+    fn test_po_errors() {
+        assert_eq!(
+            Catalog::from_po("msgid \"a\"\nmsgstr \"b\n"),
+            Err(CatalogError::Syntax { line: 2 })
+        );
+        assert_eq!(
+            Catalog::from_po("\"orphan\"\n"),
+            Err(CatalogError::Syntax { line: 1 })
+        );
+        assert_eq!(
+            Catalog::from_po("msgid \"a\"\nmsgwhat \"b\"\n"),
+            Err(CatalogError::Syntax { line: 2 })
+        );
+        assert_eq!(
+            Catalog::from_po("msgid \"a\\q\"\n"),
+            Err(CatalogError::Syntax { line: 1 })
+        );
+        assert_eq!(
+            Catalog::from_po("msgid \"a\"\nmsgid \"b\"\n"),
+            Err(CatalogError::Syntax { line: 2 })
+        );
+        assert_eq!(
+            Catalog::from_po("msgid \"\"\nmsgstr \"Plural-Forms: nplurals=2; plural=n+;\\n\"\n"),
+            Err(CatalogError::InvalidPluralForms)
+        );
+    }
+
+    /// A `.mo` file in the layout `msgfmt` writes, without a hash table.
+    fn mo(messages: &[(&str, &str)], big_endian: bool) -> Vec<u8> {
+        let word = |v: usize| {
+            let v = v as u32;
+            if big_endian {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            }
+        };
+        let count = messages.len();
+        let originals = 28;
+        let translations = originals + count * 8;
+        let mut strings = translations + count * 8;
+
+        let mut out = Vec::new();
+        for v in [0x9504_12DE, 0, count, originals, translations, 0, 0] {
+            out.extend_from_slice(&word(v));
+        }
+        let mut data = Vec::new();
+        for pick in [0, 1] {
+            for &(original, translation) in messages {
+                let s = if pick == 0 { original } else { translation };
+                out.extend_from_slice(&word(s.len()));
+                out.extend_from_slice(&word(strings));
+                data.extend_from_slice(s.as_bytes());
+                data.push(0);
+                strings += s.len() + 1;
+            }
+        }
+        out.extend_from_slice(&data);
+        out
+    }
+
+    #[test]
+    fn test_mo_both_byte_orders() {
+        let messages = [
+            (
+                "",
+                "Plural-Forms: nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;\n",
+            ),
+            ("Close", "Fermer"),
+            ("menu\u{4}Edit", "Édition"),
+            (
+                "%d item\0%d items",
+                "%d élément\0%d éléments (2)\0%d éléments",
+            ),
+        ];
+        for big_endian in [false, true] {
+            let catalog = Catalog::from_mo(&mo(&messages, big_endian)).unwrap();
+            assert_eq!(catalog.len(), 3);
+            assert_eq!(catalog.plural_rule().nplurals(), 3);
+            assert_eq!(catalog.gettext("Close"), "Fermer");
+            assert_eq!(catalog.pgettext("menu", "Edit"), "Édition");
+            assert_eq!(catalog.gettext("Edit"), "Edit");
+            assert_eq!(
+                catalog.ngettext("%d item", "%d items", 2),
+                "%d éléments (2)"
+            );
+            assert_eq!(catalog.ngettext("%d item", "%d items", 9), "%d éléments");
+        }
+    }
+
+    #[test]
+    fn test_mo_errors() {
+        let good = mo(&[("a", "b")], false);
+        assert_eq!(Catalog::from_mo(&good[..20]), Err(CatalogError::Truncated));
+        assert_eq!(
+            Catalog::from_mo(&good[..good.len() - 1]),
+            Err(CatalogError::Truncated)
+        );
+        let mut bad = good.clone();
+        bad[0] ^= 1;
+        assert_eq!(Catalog::from_mo(&bad), Err(CatalogError::BadMagic));
+        let mut bad = good.clone();
+        bad[6] = 2;
+        assert_eq!(
+            Catalog::from_mo(&bad),
+            Err(CatalogError::UnsupportedRevision)
+        );
+        let mut bad = good.clone();
+        let last = bad.len() - 2;
+        bad[last] = 0xFF;
+        assert_eq!(Catalog::from_mo(&bad), Err(CatalogError::NotUtf8));
+        let mut bad = good;
+        bad[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(Catalog::from_mo(&bad), Err(CatalogError::Truncated));
+
+        assert_eq!(
+            format!("{}", CatalogError::Syntax { line: 3 }),
+            "line 3: malformed .po entry"
+        );
+    }
+}
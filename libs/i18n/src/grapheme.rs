@@ -0,0 +1,235 @@
+//! Grapheme cluster boundaries.
+//!
+//! A user-perceived character can span several code points: `e` followed
+//! by a combining acute accent, an emoji with a skin-tone modifier, a
+//! family joined with ZWJ, or a flag made of two regional indicators.
+//! Editors move the cursor and delete by these clusters so they never
+//! split one.
+//!
+//! This implements the parts of UAX #29 extended grapheme clusters that
+//! matter for the text VeridianOS renders: CR LF, control characters,
+//! combining marks and other extenders, spacing marks, ZWJ emoji sequences
+//! and regional-indicator pairs. Hangul syllable composition and prepend
+//! characters are not handled; those code points form clusters of one.
+
+/// Zero width joiner.
+const ZWJ: char = '\u{200D}';
+
+/// Code points that attach to the preceding character: combining marks,
+/// variation selectors, emoji modifiers and the joiners.
+pub fn is_extend(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036F     // Combining Diacritical Marks
+            | 0x0483..=0x0489   // Cyrillic
+            | 0x0591..=0x05BD   // Hebrew points
+            | 0x05BF | 0x05C1..=0x05C2 | 0x05C4..=0x05C5 | 0x05C7
+            | 0x0610..=0x061A   // Arabic
+            | 0x064B..=0x065F
+            | 0x0670
+            | 0x06D6..=0x06DC
+            | 0x06DF..=0x06E4
+            | 0x06E7..=0x06E8
+            | 0x06EA..=0x06ED
+            | 0x0900..=0x0902   // Devanagari
+            | 0x093A | 0x093C | 0x0941..=0x0948 | 0x094D | 0x0951..=0x0957
+            | 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E // Thai
+            | 0x1AB0..=0x1AFF   // Combining Diacritical Marks Extended
+            | 0x1DC0..=0x1DFF   // Combining Diacritical Marks Supplement
+            | 0x200C..=0x200D   // ZWNJ, ZWJ
+            | 0x20D0..=0x20FF   // Combining Marks for Symbols
+            | 0x302A..=0x302F   // CJK tone marks
+            | 0x3099..=0x309A   // Kana voicing marks
+            | 0xFE00..=0xFE0F   // Variation Selectors
+            | 0xFE20..=0xFE2F   // Combining Half Marks
+            | 0x1F3FB..=0x1F3FF // Emoji skin tone modifiers
+            | 0xE0020..=0xE007F // Tags
+            | 0xE0100..=0xE01EF // Variation Selectors Supplement
+    )
+}
+
+/// Vowel signs that are drawn with width but still belong to the
+/// preceding consonant.
+fn is_spacing_mark(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0903 | 0x093B | 0x093E..=0x0940 | 0x0949..=0x094C | 0x094E..=0x094F
+            | 0x0E33
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Approximation of `Extended_Pictographic`: the emoji and symbol blocks.
+fn is_pictographic(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139
+            | 0x2194..=0x21AA
+            | 0x231A..=0x23FF
+            | 0x25AA..=0x25FE
+            | 0x2600..=0x27BF
+            | 0x2934..=0x2935
+            | 0x2B05..=0x2B55
+            | 0x3030 | 0x303D | 0x3297 | 0x3299
+            | 0x1F000..=0x1F1E5
+            | 0x1F200..=0x1FAFF
+    )
+}
+
+fn is_control(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{2028}' | '\u{2029}')
+}
+
+/// What has been seen of the current cluster, for the rules that look
+/// further back than one character.
+#[derive(Debug, Clone, Copy, Default)]
+struct State {
+    /// Regional indicators ending the cluster so far.
+    regional: usize,
+    /// The cluster so far is a pictograph followed only by extenders.
+    pictographic: bool,
+}
+
+impl State {
+    fn start(c: char) -> Self {
+        Self {
+            regional: usize::from(is_regional_indicator(c)),
+            pictographic: is_pictographic(c),
+        }
+    }
+
+    /// Whether `next` continues the cluster that `prev` ends; if so the
+    /// state takes `next` in.
+    fn joins(&mut self, prev: char, next: char) -> bool {
+        let joins = if prev == '\r' && next == '\n' {
+            true
+        } else if is_control(prev) || is_control(next) {
+            false
+        } else {
+            is_extend(next)
+                || is_spacing_mark(next)
+                || (prev == ZWJ && self.pictographic && is_pictographic(next))
+                || (is_regional_indicator(prev)
+                    && is_regional_indicator(next)
+                    && self.regional % 2 == 1)
+        };
+        if joins {
+            self.regional += usize::from(is_regional_indicator(next));
+            if !(is_extend(next) || is_pictographic(next)) {
+                self.pictographic = false;
+            }
+        }
+        joins
+    }
+}
+
+/// Byte length of the grapheme cluster at the start of `text`.
+fn cluster_len(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    let Some((_, first)) = chars.next() else {
+        return 0;
+    };
+    let mut state = State::start(first);
+    let mut prev = first;
+    for (index, c) in chars {
+        if !state.joins(prev, c) {
+            return index;
+        }
+        prev = c;
+    }
+    text.len()
+}
+
+/// Byte offset of the cluster boundary after `offset`, or `text.len()`.
+/// `offset` should be a cluster boundary; a character boundary inside a
+/// cluster moves to the end of what follows.
+pub fn next_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset + cluster_len(&text[offset..])
+}
+
+/// Byte offset of the cluster boundary before `offset`, or 0.
+pub fn prev_boundary(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    // Regional-indicator pairing depends on everything before, so walk
+    // forward from the start
+    let mut at = 0;
+    loop {
+        let next = at + cluster_len(&text[at..]);
+        if next >= offset || next == at {
+            return at;
+        }
+        at = next;
+    }
+}
+
+/// The grapheme clusters of `text`, in order.
+pub fn clusters(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    core::iter::from_fn(move || {
+        let len = cluster_len(rest);
+        if len == 0 {
+            return None;
+        }
+        let (cluster, tail) = rest.split_at(len);
+        rest = tail;
+        Some(cluster)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn split(text: &str) -> Vec<&str> {
+        clusters(text).collect()
+    }
+
+    #[test]
+    fn test_clusters() {
+        assert_eq!(split("abc"), ["a", "b", "c"]);
+        assert_eq!(split("e\u{301}x"), ["e\u{301}", "x"]);
+        assert_eq!(split("a\r\nb\n\r"), ["a", "\r\n", "b", "\n", "\r"]);
+        // Skin tone, ZWJ family, keycap, flags
+        assert_eq!(split("👍🏽!"), ["👍🏽", "!"]);
+        assert_eq!(
+            split("👩\u{200D}👩\u{200D}👧x"),
+            ["👩\u{200D}👩\u{200D}👧", "x"]
+        );
+        assert_eq!(split("1\u{FE0F}\u{20E3}"), ["1\u{FE0F}\u{20E3}"]);
+        assert_eq!(split("🇩🇪🇫🇷🇮"), ["🇩🇪", "🇫🇷", "🇮"]);
+        // ZWJ only joins pictographs
+        assert_eq!(split("a\u{200D}b"), ["a\u{200D}", "b"]);
+        // Devanagari consonant with a spacing vowel sign
+        assert_eq!(split("कि"), ["कि"]);
+        // A mark after a control character stands alone
+        assert_eq!(split("\n\u{301}"), ["\n", "\u{301}"]);
+        assert!(split("").is_empty());
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let text = "ae\u{301}🇩🇪🇫🇷z";
+        assert_eq!(next_boundary(text, 0), 1);
+        assert_eq!(next_boundary(text, 1), 4);
+        assert_eq!(next_boundary(text, 4), 12);
+        assert_eq!(next_boundary(text, 12), 20);
+        assert_eq!(next_boundary(text, 21), 21);
+        assert_eq!(prev_boundary(text, 21), 20);
+        assert_eq!(prev_boundary(text, 20), 12);
+        assert_eq!(prev_boundary(text, 12), 4);
+        assert_eq!(prev_boundary(text, 4), 1);
+        assert_eq!(prev_boundary(text, 3), 1);
+        assert_eq!(prev_boundary(text, 0), 0);
+        // From inside a character
+        assert_eq!(next_boundary(text, 3), 4);
+    }
+}
@@ -0,0 +1,153 @@
+//! Localization and UTF-8 text handling shared by the kernel desktop and
+//! userland.
+//!
+//! - [`catalog`]: gettext-style message catalogs, read from compiled `.mo`
+//!   files or straight from `.po` sources, with `msgctxt` and plural forms
+//! - [`plural`]: the C-like `Plural-Forms` expression language
+//! - [`utf8`]: an incremental decoder for byte streams such as terminal output,
+//!   replacing malformed input with U+FFFD
+//! - [`grapheme`]: user-perceived character boundaries, for cursor movement and
+//!   deletion in editors
+//!
+//! [`fill`] substitutes arguments into translated templates, and
+//! [`catalog_paths`] says where a language's catalog lives.
+//!
+//! The crate is `no_std` and only needs `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod catalog;
+pub mod grapheme;
+pub mod plural;
+pub mod utf8;
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::{self, Write};
+
+pub use catalog::{Catalog, CatalogError};
+pub use plural::PluralRule;
+
+/// Directory holding the catalogs, as
+/// `<LOCALE_DIR>/<language>/LC_MESSAGES/<domain>.mo`.
+pub const LOCALE_DIR: &str = "/usr/share/locale";
+
+/// The catalog paths to try for `language` and `domain`, most specific
+/// first.
+///
+/// `language` is a POSIX locale name such as `pt_BR.UTF-8@euro`; the
+/// codeset and modifier are dropped, then the territory, so `pt_BR` falls
+/// back to `pt`. Each candidate is tried as a compiled `.mo` and then as a
+/// `.po` source. `C`, `POSIX` and the empty name have no catalogs.
+pub fn catalog_paths(language: &str, domain: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let base = language.split(['.', '@']).next().unwrap_or_default();
+    if matches!(base, "" | "C" | "POSIX") {
+        return paths;
+    }
+    let mut candidates = vec![base];
+    if let Some((lang, _territory)) = base.split_once('_') {
+        candidates.push(lang);
+    }
+    for candidate in candidates {
+        for ext in ["mo", "po"] {
+            paths.push(format!(
+                "{}/{}/LC_MESSAGES/{}.{}",
+                LOCALE_DIR, candidate, domain, ext
+            ));
+        }
+    }
+    paths
+}
+
+/// Whether `language` is acceptable as a locale name: letters, digits and
+/// `_ - . @`, at most 32 bytes. The empty name selects no translation.
+pub fn is_valid_language(language: &str) -> bool {
+    language.len() <= 32
+        && language
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.' | b'@'))
+}
+
+/// Substitute `args` into a translated message template.
+///
+/// `{}` takes the next argument and `{N}` argument `N`, so a translation
+/// can put the arguments in a different order than the source string.
+/// `{{` and `}}` are literal braces. Placeholders without an argument are
+/// left as written.
+pub fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let placeholder = tail
+            .strip_prefix('{')
+            .and_then(|t| t.split_once('}'))
+            .filter(|(index, _)| index.bytes().all(|b| b.is_ascii_digit()));
+        let Some((index, after)) = placeholder else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+        let index = if index.is_empty() {
+            next += 1;
+            next - 1
+        } else {
+            index.parse().unwrap_or(usize::MAX)
+        };
+        match args.get(index) {
+            Some(arg) => {
+                let _ = write!(out, "{}", arg);
+            }
+            None => out.push_str(&tail[..tail.len() - after.len()]),
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_paths() {
+        assert_eq!(
+            catalog_paths("pt_BR.UTF-8@euro", "vsh"),
+            [
+                "/usr/share/locale/pt_BR/LC_MESSAGES/vsh.mo",
+                "/usr/share/locale/pt_BR/LC_MESSAGES/vsh.po",
+                "/usr/share/locale/pt/LC_MESSAGES/vsh.mo",
+                "/usr/share/locale/pt/LC_MESSAGES/vsh.po",
+            ]
+        );
+        assert_eq!(catalog_paths("de", "x").len(), 2);
+        assert!(catalog_paths("C.UTF-8", "x").is_empty());
+        assert!(catalog_paths("", "x").is_empty());
+
+        assert!(is_valid_language("sr_RS@latin"));
+        assert!(is_valid_language(""));
+        assert!(!is_valid_language("../../etc"));
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            fill("{}: command not found", &[&"ls"]),
+            "ls: command not found"
+        );
+        assert_eq!(fill("{1} von {0}", &[&3, &"Seite 1"]), "Seite 1 von 3");
+        assert_eq!(fill("{} + {} = {2}", &[&1, &2, &3]), "1 + 2 = 3");
+        assert_eq!(fill("{{}} {x} {5} }", &[&1]), "{} {x} {5} }");
+        assert_eq!(fill("ünïcödé {}", &[&'✓']), "ünïcödé ✓");
+    }
+}
@@ -0,0 +1,338 @@
+//! `Plural-Forms` expressions.
+//!
+//! A catalog header such as
+//!
+//! ```text
+//! Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);
+//! ```
+//!
+//! selects which translation of a plural message to use for a count `n`.
+//! The expression is a subset of C: `n`, unsigned integer literals,
+//! parentheses, `!`, `* / %`, `+ -`, comparisons, `== !=`, `&& ||` and
+//! `?:`. Arithmetic wraps and division by zero yields 0 rather than
+//! failing.
+
+use alloc::boxed::Box;
+
+use crate::CatalogError;
+
+/// Deepest nesting accepted, so a hostile catalog cannot exhaust the stack.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    N,
+    Num(u64),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, n: u64) -> u64 {
+        match self {
+            Expr::N => n,
+            Expr::Num(v) => *v,
+            Expr::Not(e) => u64::from(e.eval(n) == 0),
+            Expr::Cond(c, t, f) => {
+                if c.eval(n) != 0 {
+                    t.eval(n)
+                } else {
+                    f.eval(n)
+                }
+            }
+            Expr::Binary(BinOp::And, a, b) => u64::from(a.eval(n) != 0 && b.eval(n) != 0),
+            Expr::Binary(BinOp::Or, a, b) => u64::from(a.eval(n) != 0 || b.eval(n) != 0),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(n), b.eval(n));
+                match op {
+                    BinOp::Mul => a.wrapping_mul(b),
+                    BinOp::Div => a.checked_div(b).unwrap_or(0),
+                    BinOp::Rem => a.checked_rem(b).unwrap_or(0),
+                    BinOp::Add => a.wrapping_add(b),
+                    BinOp::Sub => a.wrapping_sub(b),
+                    BinOp::Lt => u64::from(a < b),
+                    BinOp::Gt => u64::from(a > b),
+                    BinOp::Le => u64::from(a <= b),
+                    BinOp::Ge => u64::from(a >= b),
+                    BinOp::Eq => u64::from(a == b),
+                    BinOp::Ne => u64::from(a != b),
+                    BinOp::And | BinOp::Or => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+/// Binary operators by precedence level, loosest first.
+const LEVELS: [&[(&str, BinOp)]; 6] = [
+    &[("||", BinOp::Or)],
+    &[("&&", BinOp::And)],
+    &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+    &[
+        ("<=", BinOp::Le),
+        (">=", BinOp::Ge),
+        ("<", BinOp::Lt),
+        (">", BinOp::Gt),
+    ],
+    &[("+", BinOp::Add), ("-", BinOp::Sub)],
+    &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+];
+
+/// Recursive-descent parser over the expression text.
+struct Parser<'a> {
+    rest: &'a str,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_space(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        if let Some(rest) = self.rest.strip_prefix(token) {
+            self.rest = rest;
+            return true;
+        }
+        false
+    }
+
+    fn enter(&mut self) -> Result<(), CatalogError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CatalogError::InvalidPluralForms);
+        }
+        Ok(())
+    }
+
+    fn conditional(&mut self) -> Result<Expr, CatalogError> {
+        self.enter()?;
+        let cond = self.binary(0)?;
+        let expr = if self.eat("?") {
+            let then = self.conditional()?;
+            if !self.eat(":") {
+                return Err(CatalogError::InvalidPluralForms);
+            }
+            let otherwise = self.conditional()?;
+            Expr::Cond(Box::new(cond), Box::new(then), Box::new(otherwise))
+        } else {
+            cond
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, CatalogError> {
+        let Some(ops) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        'outer: loop {
+            for &(token, op) in ops.iter() {
+                self.skip_space();
+                if self.rest.starts_with(token) {
+                    self.rest = &self.rest[token.len()..];
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, CatalogError> {
+        self.skip_space();
+        if self.rest.starts_with('!') && !self.rest.starts_with("!=") {
+            self.rest = &self.rest[1..];
+            self.enter()?;
+            let inner = self.unary()?;
+            self.depth -= 1;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, CatalogError> {
+        self.skip_space();
+        if self.eat("(") {
+            let inner = self.conditional()?;
+            if !self.eat(")") {
+                return Err(CatalogError::InvalidPluralForms);
+            }
+            return Ok(inner);
+        }
+        if self.eat("n") {
+            return Ok(Expr::N);
+        }
+        let digits = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let value = self.rest[..digits]
+            .parse()
+            .map_err(|_| CatalogError::InvalidPluralForms)?;
+        self.rest = &self.rest[digits..];
+        Ok(Expr::Num(value))
+    }
+}
+
+/// A catalog's plural rule: how many forms there are and which one a count
+/// selects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluralRule {
+    nplurals: usize,
+    expr: Expr,
+}
+
+impl Default for PluralRule {
+    /// The English rule, `nplurals=2; plural=(n != 1);`, which gettext
+    /// also uses when a catalog does not say.
+    fn default() -> Self {
+        Self {
+            nplurals: 2,
+            expr: Expr::Binary(BinOp::Ne, Box::new(Expr::N), Box::new(Expr::Num(1))),
+        }
+    }
+}
+
+impl PluralRule {
+    /// Parse the value of a `Plural-Forms` header field.
+    pub fn parse(value: &str) -> Result<Self, CatalogError> {
+        let mut nplurals = None;
+        let mut expr = None;
+        for field in value.split(';') {
+            let Some((name, val)) = field.split_once('=') else {
+                continue;
+            };
+            match name.trim() {
+                "nplurals" => {
+                    nplurals = val.trim().parse::<usize>().ok().filter(|&n| n >= 1);
+                }
+                "plural" => {
+                    let mut parser = Parser {
+                        rest: val,
+                        depth: 0,
+                    };
+                    let parsed = parser.conditional()?;
+                    parser.skip_space();
+                    if !parser.rest.is_empty() {
+                        return Err(CatalogError::InvalidPluralForms);
+                    }
+                    expr = Some(parsed);
+                }
+                _ => {}
+            }
+        }
+        match (nplurals, expr) {
+            (Some(nplurals), Some(expr)) => Ok(Self { nplurals, expr }),
+            _ => Err(CatalogError::InvalidPluralForms),
+        }
+    }
+
+    /// Number of plural forms.
+    pub fn nplurals(&self) -> usize {
+        self.nplurals
+    }
+
+    /// The form to use for `n`, always below [`PluralRule::nplurals`].
+    pub fn index(&self, n: u64) -> usize {
+        let form = self.expr.eval(n);
+        usize::try_from(form)
+            .unwrap_or(usize::MAX)
+            .min(self.nplurals - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn indices(rule: &PluralRule, counts: &[u64]) -> Vec<usize> {
+        counts.iter().map(|&n| rule.index(n)).collect()
+    }
+
+    #[test]
+    fn test_default_rule() {
+        let rule = PluralRule::default();
+        assert_eq!(indices(&rule, &[0, 1, 2, 5]), [1, 0, 1, 1]);
+        assert_eq!(
+            PluralRule::parse("nplurals=2; plural=(n != 1);"),
+            Ok(PluralRule::default())
+        );
+    }
+
+    #[test]
+    fn test_slavic_rule() {
+        let rule = PluralRule::parse(
+            "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || \
+             n%100>=20) ? 1 : 2);",
+        )
+        .unwrap();
+        assert_eq!(rule.nplurals(), 3);
+        assert_eq!(
+            indices(&rule, &[1, 2, 4, 5, 11, 12, 21, 22, 25, 111, 101]),
+            [0, 1, 1, 2, 2, 2, 0, 1, 2, 2, 0]
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        let rule = PluralRule::parse("nplurals=6; plural=!(n>3) + n/0 + (n-1)*2%5;").unwrap();
+        assert_eq!(rule.index(1), 1);
+        assert_eq!(rule.index(3), 5);
+        assert_eq!(rule.index(4), 1);
+        // Out of range forms are clamped
+        let rule = PluralRule::parse("nplurals=3; plural=n;").unwrap();
+        assert_eq!(indices(&rule, &[0, 2, 7]), [0, 2, 2]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        for bad in [
+            "nplurals=2;",
+            "plural=n;",
+            "nplurals=0; plural=0;",
+            "nplurals=2; plural=n = 1;",
+            "nplurals=2; plural=(n;",
+            "nplurals=2; plural=n ? 1;",
+            "nplurals=2; plural=x;",
+        ] {
+            assert_eq!(
+                PluralRule::parse(bad),
+                Err(CatalogError::InvalidPluralForms),
+                "{bad}"
+            );
+        }
+        let deep = alloc::format!(
+            "nplurals=2; plural={}n{};",
+            "(".repeat(100),
+            ")".repeat(100)
+        );
+        assert_eq!(
+            PluralRule::parse(&deep),
+            Err(CatalogError::InvalidPluralForms)
+        );
+    }
+}
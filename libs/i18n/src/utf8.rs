@@ -0,0 +1,200 @@
+//! Incremental UTF-8 decoding.
+//!
+//! Terminals and pipes deliver text a byte at a time, and a read may end in
+//! the middle of a character. [`Decoder`] keeps the partial sequence
+//! between calls. Malformed input -- stray continuation bytes, overlong
+//! forms, surrogates, values past U+10FFFF and sequences cut short by
+//! another lead byte -- decodes to one U+FFFD per bad sequence, the
+//! replacement policy of `String::from_utf8_lossy`.
+
+/// Streaming UTF-8 decoder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Decoder {
+    /// Code point bits collected so far.
+    partial: u32,
+    /// Continuation bytes still expected.
+    needed: u8,
+    /// Smallest code point the current sequence length may encode.
+    min: u32,
+}
+
+/// What one byte produced: nothing yet, one character, or a replacement for
+/// an interrupted sequence followed by the character the byte started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    chars: [Option<char>; 2],
+    next: usize,
+}
+
+impl Decoded {
+    const NONE: Self = Self {
+        chars: [None, None],
+        next: 0,
+    };
+
+    fn one(c: char) -> Self {
+        Self {
+            chars: [Some(c), None],
+            next: 0,
+        }
+    }
+
+    fn then(mut self, c: Option<char>) -> Self {
+        let slot = if self.chars[0].is_none() { 0 } else { 1 };
+        self.chars[slot] = c;
+        self
+    }
+}
+
+impl Iterator for Decoded {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = (*self.chars.get(self.next)?)?;
+        self.next += 1;
+        Some(c)
+    }
+}
+
+impl Decoder {
+    /// A decoder between characters.
+    pub const fn new() -> Self {
+        Self {
+            partial: 0,
+            needed: 0,
+            min: 0,
+        }
+    }
+
+    /// Whether a sequence has been started but not finished.
+    pub fn is_pending(&self) -> bool {
+        self.needed != 0
+    }
+
+    /// Feed one byte and collect the characters it completes.
+    pub fn push(&mut self, byte: u8) -> Decoded {
+        if self.needed != 0 {
+            if byte & 0xC0 == 0x80 {
+                self.partial = (self.partial << 6) | u32::from(byte & 0x3F);
+                self.needed -= 1;
+                if self.needed != 0 {
+                    return Decoded::NONE;
+                }
+                let c = Some(self.partial)
+                    .filter(|&cp| cp >= self.min)
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                return Decoded::one(c);
+            }
+            // Cut short: replace what we had and start over with this byte
+            self.needed = 0;
+            return Decoded::one(char::REPLACEMENT_CHARACTER).then(self.start(byte));
+        }
+        Decoded::NONE.then(self.start(byte))
+    }
+
+    /// Begin a sequence with `byte`, returning it if it is a whole
+    /// character by itself.
+    fn start(&mut self, byte: u8) -> Option<char> {
+        let (needed, bits, min) = match byte {
+            0x00..=0x7F => return Some(char::from(byte)),
+            0xC0..=0xDF => (1, byte & 0x1F, 0x80),
+            0xE0..=0xEF => (2, byte & 0x0F, 0x800),
+            0xF0..=0xF4 => (3, byte & 0x07, 0x1_0000),
+            // Continuation without a lead byte, or a lead byte that can
+            // only encode values past U+10FFFF
+            _ => return Some(char::REPLACEMENT_CHARACTER),
+        };
+        self.needed = needed;
+        self.partial = u32::from(bits);
+        self.min = min;
+        None
+    }
+
+    /// End of input: a replacement character if a sequence was left
+    /// unfinished.
+    pub fn finish(&mut self) -> Option<char> {
+        let pending = self.is_pending();
+        *self = Self::new();
+        pending.then_some(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+/// The characters of `bytes`, decoded lossily.
+pub fn chars_lossy(bytes: &[u8]) -> impl Iterator<Item = char> + '_ {
+    let mut decoder = Decoder::new();
+    let mut tail = false;
+    bytes
+        .iter()
+        .map(Some)
+        .chain(core::iter::once(None))
+        .flat_map(move |byte| match byte {
+            Some(&b) => decoder.push(b),
+            None if !tail => {
+                tail = true;
+                Decoded::NONE.then(decoder.finish())
+            }
+            None => Decoded::NONE,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> String {
+        chars_lossy(bytes).collect()
+    }
+
+    #[test]
+    fn test_valid_text() {
+        let text = "aé€😀\u{10FFFF}\u{0}";
+        assert_eq!(decode(text.as_bytes()), text);
+
+        // One byte at a time, as a terminal sees it
+        let mut decoder = Decoder::new();
+        let mut out = String::new();
+        for &b in "ñ😀".as_bytes() {
+            out.extend(decoder.push(b));
+        }
+        assert!(!decoder.is_pending());
+        assert_eq!(out, "ñ😀");
+    }
+
+    #[test]
+    fn test_malformed_matches_std() {
+        let cases: [&[u8]; 9] = [
+            b"\x80abc",
+            b"a\xC3",
+            b"a\xC3b",
+            b"\xE2\x82",
+            b"\xE2\x82\xE2\x82\xAC",
+            b"\xC0\xAF",
+            b"\xED\xA0\x80",
+            b"\xF4\x90\x80\x80",
+            b"\xF8\x88\x80\x80\x80",
+        ];
+        for bytes in cases {
+            let expected = String::from_utf8_lossy(bytes);
+            // std replaces maximal subparts; we replace whole sequences,
+            // which differs only in how many U+FFFD appear
+            let ours = decode(bytes);
+            let squash = |s: &str| {
+                let mut out: Vec<char> = Vec::new();
+                for c in s.chars() {
+                    if !(c == char::REPLACEMENT_CHARACTER
+                        && out.last() == Some(&char::REPLACEMENT_CHARACTER))
+                    {
+                        out.push(c);
+                    }
+                }
+                out
+            };
+            assert_eq!(squash(&ours), squash(&expected), "{bytes:x?}");
+        }
+        assert_eq!(decode(b"a\xC3b"), "a\u{FFFD}b");
+        assert_eq!(decode(b"\xE2\x82\xE2\x82\xAC"), "\u{FFFD}€");
+    }
+}
@@ -0,0 +1,340 @@
+//! Windows BMP decoder and encoder.
+//!
+//! Decodes uncompressed images at 1/4/8 bpp (palette), 16/24/32 bpp and
+//! `BI_BITFIELDS`/`BI_ALPHABITFIELDS` images, with OS/2 core, `INFO`, `V4`
+//! and `V5` headers, stored bottom-up or top-down. RLE compression is not
+//! supported.
+//!
+//! A 32 bpp `BI_RGB` image whose fourth bytes are all zero is treated as
+//! opaque, since most writers leave that byte unused. The encoder writes
+//! 32 bpp top-down `BI_RGB` with the alpha in the fourth byte.
+
+use alloc::vec::Vec;
+
+use crate::{checked_image, read_le_u16, read_le_u32, Image, ImageError};
+
+/// "BM" file signature.
+pub const SIGNATURE: [u8; 2] = *b"BM";
+
+/// File header (14) + `BITMAPINFOHEADER` (40), as written by the encoder.
+pub const HEADER_SIZE: usize = 54;
+
+/// Size of the OS/2 `BITMAPCOREHEADER`.
+const CORE_HEADER_SIZE: u32 = 12;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// One channel of a bit-field pixel layout.
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    mask: u32,
+    shift: u32,
+    bits: u32,
+}
+
+impl Channel {
+    fn new(mask: u32) -> Self {
+        Self {
+            mask,
+            shift: if mask == 0 { 0 } else { mask.trailing_zeros() },
+            bits: mask.count_ones(),
+        }
+    }
+
+    /// Extract this channel from `px` and scale it to 8 bits.
+    fn extract(self, px: u32) -> u8 {
+        let v = (px & self.mask) >> self.shift;
+        match self.bits {
+            0 => 0,
+            1..=7 => (v * 255 / ((1 << self.bits) - 1)) as u8,
+            bits => (v >> (bits - 8)) as u8,
+        }
+    }
+}
+
+/// Decode a BMP file.
+pub fn decode_bmp(data: &[u8]) -> Result<Image, ImageError> {
+    if data.len() < 14 + CORE_HEADER_SIZE as usize {
+        return Err(if data.starts_with(&SIGNATURE) {
+            ImageError::TruncatedData
+        } else {
+            ImageError::InvalidSignature
+        });
+    }
+    if !data.starts_with(&SIGNATURE) {
+        return Err(ImageError::InvalidSignature);
+    }
+
+    let pixel_offset = read_le_u32(data, 10) as usize;
+    let dib_size = read_le_u32(data, 14);
+    let (width, height, bpp, compression) = if dib_size == CORE_HEADER_SIZE {
+        (
+            read_le_u16(data, 18) as i32,
+            read_le_u16(data, 20) as i32,
+            read_le_u16(data, 24),
+            BI_RGB,
+        )
+    } else if dib_size >= 40 {
+        if data.len() < 14 + 40 {
+            return Err(ImageError::TruncatedData);
+        }
+        (
+            read_le_u32(data, 18) as i32,
+            read_le_u32(data, 22) as i32,
+            read_le_u16(data, 28),
+            read_le_u32(data, 30),
+        )
+    } else {
+        return Err(ImageError::Unsupported);
+    };
+
+    if width <= 0 || height == 0 || height == i32::MIN {
+        return Err(ImageError::InvalidDimensions);
+    }
+    let top_down = height < 0;
+    let mut image = checked_image(width as u32, height.unsigned_abs())?;
+    let (w, h) = (image.width as usize, image.height as usize);
+
+    let row_size = (w * bpp as usize).div_ceil(32) * 4;
+    let pixels = data
+        .get(pixel_offset..)
+        .filter(|p| p.len() >= row_size * h)
+        .ok_or(ImageError::TruncatedData)?;
+
+    let layout = match (bpp, compression) {
+        (1 | 4 | 8, BI_RGB) => Layout::Palette(read_palette(data, dib_size, bpp)?),
+        (16, BI_RGB) => Layout::Fields(bitfields(0x7C00, 0x03E0, 0x001F, 0)),
+        (24, BI_RGB) => Layout::Bgr,
+        (32, BI_RGB) => Layout::Bgra {
+            has_alpha: pixels[..row_size * h].chunks_exact(4).any(|px| px[3] != 0),
+        },
+        (16 | 32, BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+            // Masks live in the V4/V5 header, or right after an INFO header.
+            let masks_at = 14 + 40;
+            let count = if compression == BI_ALPHABITFIELDS || dib_size >= 56 {
+                4
+            } else {
+                3
+            };
+            if data.len() < masks_at + count * 4 {
+                return Err(ImageError::TruncatedData);
+            }
+            let alpha = if count == 4 {
+                read_le_u32(data, masks_at + 12)
+            } else {
+                0
+            };
+            Layout::Fields(bitfields(
+                read_le_u32(data, masks_at),
+                read_le_u32(data, masks_at + 4),
+                read_le_u32(data, masks_at + 8),
+                alpha,
+            ))
+        }
+        _ => return Err(ImageError::Unsupported),
+    };
+
+    for y in 0..h {
+        let src_row = if top_down { y } else { h - 1 - y };
+        let row = &pixels[src_row * row_size..(src_row + 1) * row_size];
+        let dst = &mut image.pixels[y * w * 4..(y + 1) * w * 4];
+        for (x, out) in dst.chunks_exact_mut(4).enumerate() {
+            let rgba = match &layout {
+                Layout::Palette(palette) => {
+                    let bits = bpp as usize;
+                    let byte = row[x * bits / 8];
+                    let shift = 8 - bits - (x * bits % 8);
+                    let index = (byte >> shift) as usize & ((1 << bits) - 1);
+                    *palette.get(index).ok_or(ImageError::CorruptData)?
+                }
+                Layout::Bgr => [row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 0xFF],
+                Layout::Bgra { has_alpha } => {
+                    let px = &row[x * 4..x * 4 + 4];
+                    [px[2], px[1], px[0], if *has_alpha { px[3] } else { 0xFF }]
+                }
+                Layout::Fields([r, g, b, a]) => {
+                    let px = if bpp == 16 {
+                        read_le_u16(row, x * 2) as u32
+                    } else {
+                        read_le_u32(row, x * 4)
+                    };
+                    let alpha = if a.bits == 0 { 0xFF } else { a.extract(px) };
+                    [r.extract(px), g.extract(px), b.extract(px), alpha]
+                }
+            };
+            out.copy_from_slice(&rgba);
+        }
+    }
+    Ok(image)
+}
+
+/// How a row's bytes map to pixels.
+enum Layout {
+    Palette(Vec<[u8; 4]>),
+    Bgr,
+    Bgra { has_alpha: bool },
+    Fields([Channel; 4]),
+}
+
+fn bitfields(r: u32, g: u32, b: u32, a: u32) -> [Channel; 4] {
+    [
+        Channel::new(r),
+        Channel::new(g),
+        Channel::new(b),
+        Channel::new(a),
+    ]
+}
+
+/// Read the color table that follows the DIB header.
+fn read_palette(data: &[u8], dib_size: u32, bpp: u16) -> Result<Vec<[u8; 4]>, ImageError> {
+    let (entry_size, mut count) = if dib_size == CORE_HEADER_SIZE {
+        (3, 0)
+    } else {
+        (4, read_le_u32(data, 46) as usize)
+    };
+    let max = 1usize << bpp;
+    if count == 0 || count > max {
+        count = max;
+    }
+    let start = 14usize.saturating_add(dib_size as usize);
+    let table = data
+        .get(start..start.saturating_add(count * entry_size))
+        .ok_or(ImageError::TruncatedData)?;
+    Ok(table
+        .chunks_exact(entry_size)
+        .map(|c| [c[2], c[1], c[0], 0xFF])
+        .collect())
+}
+
+/// Encode as a 32 bpp top-down BMP.
+pub fn encode_bmp(image: &Image) -> Result<Vec<u8>, ImageError> {
+    let count = (image.width as usize) * (image.height as usize);
+    if image.width == 0
+        || image.height == 0
+        || image.width > i32::MAX as u32
+        || image.height > i32::MAX as u32
+        || image.pixels.len() != count * 4
+    {
+        return Err(ImageError::InvalidDimensions);
+    }
+    let file_size =
+        u32::try_from(HEADER_SIZE + count * 4).map_err(|_| ImageError::InvalidDimensions)?;
+
+    let mut bmp = Vec::with_capacity(file_size as usize);
+
+    // -- 14-byte file header --
+    bmp.extend_from_slice(&SIGNATURE);
+    bmp.extend_from_slice(&file_size.to_le_bytes());
+    bmp.extend_from_slice(&[0u8; 4]); // reserved
+    bmp.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes()); // pixel data offset
+
+    // -- 40-byte BITMAPINFOHEADER --
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&(image.width as i32).to_le_bytes());
+    bmp.extend_from_slice(&(-(image.height as i32)).to_le_bytes()); // negative = top-down
+    bmp.extend_from_slice(&1u16.to_le_bytes()); // planes
+    bmp.extend_from_slice(&32u16.to_le_bytes()); // bpp
+    bmp.extend_from_slice(&BI_RGB.to_le_bytes());
+    bmp.extend_from_slice(&[0u8; 20]); // image size, resolution, palette counts
+
+    for px in image.pixels.chunks_exact(4) {
+        bmp.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+    }
+    Ok(bmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    /// Build a BMP with a 40-byte INFO header around `pixels`.
+    fn info_bmp(width: i32, height: i32, bpp: u16, extra: &[u8], pixels: &[u8]) -> Vec<u8> {
+        let offset = (HEADER_SIZE + extra.len()) as u32;
+        let mut bmp = Vec::new();
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(offset + pixels.len() as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&offset.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&width.to_le_bytes());
+        bmp.extend_from_slice(&height.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&bpp.to_le_bytes());
+        let compression = if bpp == 16 && !extra.is_empty() {
+            BI_BITFIELDS
+        } else {
+            BI_RGB
+        };
+        bmp.extend_from_slice(&compression.to_le_bytes());
+        bmp.extend_from_slice(&[0; 20]);
+        bmp.extend_from_slice(extra);
+        bmp.extend_from_slice(pixels);
+        bmp
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut img = Image::new(3, 2);
+        img.set_pixel(0, 0, 255, 0, 0, 255);
+        img.set_pixel(2, 1, 1, 2, 3, 4);
+        let bmp = encode_bmp(&img).unwrap();
+        assert_eq!(bmp.len(), HEADER_SIZE + 6 * 4);
+        assert_eq!(decode_bmp(&bmp).unwrap(), img);
+    }
+
+    #[test]
+    fn test_24bpp_bottom_up_padded() {
+        // 1x2, rows padded from 3 to 4 bytes; the first row stored is the
+        // bottom one.
+        let bmp = info_bmp(1, 2, 24, &[], &[3, 2, 1, 0, 6, 5, 4, 0]);
+        let img = decode_bmp(&bmp).unwrap();
+        assert_eq!(img.pixels, vec![4, 5, 6, 0xFF, 1, 2, 3, 0xFF]);
+    }
+
+    #[test]
+    fn test_32bpp_zero_alpha_is_opaque() {
+        let bmp = info_bmp(2, -1, 32, &[], &[1, 2, 3, 0, 4, 5, 6, 0]);
+        let img = decode_bmp(&bmp).unwrap();
+        assert!(img.is_opaque());
+        assert_eq!(img.get_pixel(1, 0), (6, 5, 4, 0xFF));
+    }
+
+    #[test]
+    fn test_1bpp_palette() {
+        let palette = [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0];
+        let bmp = info_bmp(3, -1, 1, &palette, &[0b1010_0000, 0, 0, 0]);
+        let img = decode_bmp(&bmp).unwrap();
+        assert_eq!(img.get_pixel(0, 0), (0xFF, 0xFF, 0xFF, 0xFF));
+        assert_eq!(img.get_pixel(1, 0), (0, 0, 0, 0xFF));
+        assert_eq!(img.get_pixel(2, 0), (0xFF, 0xFF, 0xFF, 0xFF));
+    }
+
+    #[test]
+    fn test_16bpp_rgb565_bitfields() {
+        let mut masks = Vec::new();
+        for mask in [0xF800u32, 0x07E0, 0x001F] {
+            masks.extend_from_slice(&mask.to_le_bytes());
+        }
+        let bmp = info_bmp(1, -1, 16, &masks, &[0xE0, 0x07, 0, 0]); // pure green
+        assert_eq!(
+            decode_bmp(&bmp).unwrap().get_pixel(0, 0),
+            (0, 0xFF, 0, 0xFF)
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert_eq!(decode_bmp(b"XX"), Err(ImageError::InvalidSignature));
+        let bmp = info_bmp(2, 2, 24, &[], &[0; 8]);
+        assert_eq!(decode_bmp(&bmp), Err(ImageError::TruncatedData));
+        let bmp = info_bmp(0, 1, 24, &[], &[0; 4]);
+        assert_eq!(decode_bmp(&bmp), Err(ImageError::InvalidDimensions));
+        let mut bmp = info_bmp(1, 1, 8, &[], &[0; 4]);
+        bmp[30] = 1; // BI_RLE8
+        assert_eq!(decode_bmp(&bmp), Err(ImageError::Unsupported));
+    }
+}
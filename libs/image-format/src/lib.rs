@@ -0,0 +1,325 @@
+//! Image decoding and encoding shared by the kernel desktop and userland.
+//!
+//! Every codec reads into and writes from one pixel format, [`Image`]
+//! (8-bit RGBA, row-major), so callers convert to their framebuffer layout
+//! in exactly one place:
+//!
+//! - [`png`]: all color types and bit depths, Adam7, zlib inflate; 8-bit
+//!   RGB/RGBA encoding with fixed-Huffman DEFLATE
+//! - [`bmp`]: uncompressed and `BI_BITFIELDS` BMP at 1/4/8/16/24/32 bpp,
+//!   top-down or bottom-up; 32 bpp encoding
+//! - [`qoi`]: the Quite OK Image format, both directions
+//!
+//! [`decode`] picks the codec from the file signature. The crate is
+//! `no_std` and only needs `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod bmp;
+pub mod png;
+pub mod qoi;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+pub use bmp::{decode_bmp, encode_bmp};
+pub use png::{decode_png, encode_png};
+pub use qoi::{decode_qoi, encode_qoi};
+
+/// Largest width or height any decoder accepts.
+pub const MAX_DIMENSION: u32 = 16384;
+
+/// Errors produced by the codecs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageError {
+    /// Data is too short or truncated.
+    TruncatedData,
+    /// Magic bytes / signature mismatch.
+    InvalidSignature,
+    /// Unsupported feature or variant.
+    Unsupported,
+    /// Corrupted or invalid data encountered during decoding.
+    CorruptData,
+    /// Checksum (CRC, Adler-32) verification failed.
+    ChecksumMismatch,
+    /// Image dimensions are zero or exceed limits.
+    InvalidDimensions,
+    /// DEFLATE decompression failed.
+    DecompressionError,
+    /// Huffman table construction failed.
+    InvalidHuffmanTable,
+}
+
+impl ImageError {
+    /// A short description of the error.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImageError::TruncatedData => "truncated image data",
+            ImageError::InvalidSignature => "not a recognized image file",
+            ImageError::Unsupported => "unsupported image variant",
+            ImageError::CorruptData => "corrupt image data",
+            ImageError::ChecksumMismatch => "image checksum mismatch",
+            ImageError::InvalidDimensions => "invalid image dimensions",
+            ImageError::DecompressionError => "image decompression failed",
+            ImageError::InvalidHuffmanTable => "invalid Huffman table",
+        }
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A decoded image with RGBA8888 pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// RGBA8888 pixel data, row-major, 4 bytes per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Create a new image filled with transparent black.
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(4))
+            .unwrap_or(0);
+        Self {
+            width,
+            height,
+            pixels: alloc::vec![0u8; size],
+        }
+    }
+
+    /// Build an image from `0xAARRGGBB` words, the layout of the desktop's
+    /// BGRA framebuffers.
+    pub fn from_argb(width: u32, height: u32, argb: &[u32]) -> Self {
+        let mut pixels = Vec::with_capacity(argb.len() * 4);
+        for &px in argb {
+            let [b, g, r, a] = px.to_le_bytes();
+            pixels.extend_from_slice(&[r, g, b, a]);
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The pixels as `0xAARRGGBB` words; the inverse of
+    /// [`Image::from_argb`].
+    pub fn to_argb(&self) -> Vec<u32> {
+        self.pixels
+            .chunks_exact(4)
+            .map(|p| u32::from_le_bytes([p[2], p[1], p[0], p[3]]))
+            .collect()
+    }
+
+    /// Set a pixel at (x, y). Out-of-bounds writes are silently ignored.
+    #[inline]
+    pub fn set_pixel(&mut self, x: u32, y: u32, r: u8, g: u8, b: u8, a: u8) {
+        if x < self.width && y < self.height {
+            let off = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
+            if off + 3 < self.pixels.len() {
+                self.pixels[off] = r;
+                self.pixels[off + 1] = g;
+                self.pixels[off + 2] = b;
+                self.pixels[off + 3] = a;
+            }
+        }
+    }
+
+    /// Get a pixel at (x, y) as (R, G, B, A).
+    #[inline]
+    pub fn get_pixel(&self, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        if x < self.width && y < self.height {
+            let off = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
+            if off + 3 < self.pixels.len() {
+                return (
+                    self.pixels[off],
+                    self.pixels[off + 1],
+                    self.pixels[off + 2],
+                    self.pixels[off + 3],
+                );
+            }
+        }
+        (0, 0, 0, 0)
+    }
+
+    /// Whether every pixel is fully opaque.
+    pub fn is_opaque(&self) -> bool {
+        self.pixels.chunks_exact(4).all(|p| p[3] == 0xFF)
+    }
+}
+
+/// Allocate an image after checking the dimensions against
+/// [`MAX_DIMENSION`].
+pub(crate) fn checked_image(width: u32, height: u32) -> Result<Image, ImageError> {
+    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(ImageError::InvalidDimensions);
+    }
+    Ok(Image::new(width, height))
+}
+
+/// Image file formats this crate understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Bmp,
+    Qoi,
+}
+
+impl Format {
+    /// Identify a format from the leading bytes of a file.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&png::SIGNATURE) {
+            Some(Format::Png)
+        } else if data.starts_with(&bmp::SIGNATURE) {
+            Some(Format::Bmp)
+        } else if data.starts_with(&qoi::SIGNATURE) {
+            Some(Format::Qoi)
+        } else {
+            None
+        }
+    }
+
+    /// Identify a format from a file name's extension (case-insensitive).
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1;
+        if ext.eq_ignore_ascii_case("png") {
+            Some(Format::Png)
+        } else if ext.eq_ignore_ascii_case("bmp") {
+            Some(Format::Bmp)
+        } else if ext.eq_ignore_ascii_case("qoi") {
+            Some(Format::Qoi)
+        } else {
+            None
+        }
+    }
+}
+
+/// Decode a PNG, BMP or QOI file, detecting the format from its signature.
+pub fn decode(data: &[u8]) -> Result<Image, ImageError> {
+    match Format::detect(data) {
+        Some(Format::Png) => decode_png(data),
+        Some(Format::Bmp) => decode_bmp(data),
+        Some(Format::Qoi) => decode_qoi(data),
+        None => Err(ImageError::InvalidSignature),
+    }
+}
+
+/// Encode `image` in `format`.
+pub fn encode(image: &Image, format: Format) -> Result<Vec<u8>, ImageError> {
+    match format {
+        Format::Png => encode_png(image),
+        Format::Bmp => encode_bmp(image),
+        Format::Qoi => encode_qoi(image),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Byte-reading helpers
+// ---------------------------------------------------------------------------
+
+#[inline]
+pub(crate) fn read_be_u16(data: &[u8], off: usize) -> u16 {
+    ((data[off] as u16) << 8) | (data[off + 1] as u16)
+}
+
+#[inline]
+pub(crate) fn read_be_u32(data: &[u8], off: usize) -> u32 {
+    ((data[off] as u32) << 24)
+        | ((data[off + 1] as u32) << 16)
+        | ((data[off + 2] as u32) << 8)
+        | (data[off + 3] as u32)
+}
+
+#[inline]
+pub(crate) fn read_le_u16(data: &[u8], off: usize) -> u16 {
+    (data[off] as u16) | ((data[off + 1] as u16) << 8)
+}
+
+#[inline]
+pub(crate) fn read_le_u32(data: &[u8], off: usize) -> u32 {
+    (data[off] as u32)
+        | ((data[off + 1] as u32) << 8)
+        | ((data[off + 2] as u32) << 16)
+        | ((data[off + 3] as u32) << 24)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Format::detect(&[137, 80, 78, 71, 13, 10, 26, 10, 0]),
+            Some(Format::Png)
+        );
+        assert_eq!(Format::detect(b"BM\0\0"), Some(Format::Bmp));
+        assert_eq!(Format::detect(b"qoif\0\0"), Some(Format::Qoi));
+        assert_eq!(Format::detect(b"GIF89a"), None);
+        assert_eq!(Format::detect(b""), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Format::from_path("/tmp/shot.PNG"), Some(Format::Png));
+        assert_eq!(Format::from_path("a.b.bmp"), Some(Format::Bmp));
+        assert_eq!(Format::from_path("logo.qoi"), Some(Format::Qoi));
+        assert_eq!(Format::from_path("README"), None);
+    }
+
+    #[test]
+    fn test_set_get_pixel() {
+        let mut img = Image::new(4, 4);
+        img.set_pixel(1, 2, 0xAA, 0xBB, 0xCC, 0xDD);
+        assert_eq!(img.get_pixel(1, 2), (0xAA, 0xBB, 0xCC, 0xDD));
+        img.set_pixel(10, 10, 255, 0, 0, 255); // ignored
+        assert_eq!(img.get_pixel(10, 10), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_argb_roundtrip() {
+        let argb = vec![0xFF11_2233, 0x8044_5566];
+        let img = Image::from_argb(2, 1, &argb);
+        assert_eq!(
+            img.pixels,
+            vec![0x11, 0x22, 0x33, 0xFF, 0x44, 0x55, 0x66, 0x80]
+        );
+        assert!(!img.is_opaque());
+        assert_eq!(img.to_argb(), argb);
+    }
+
+    #[test]
+    fn test_decode_all_formats() {
+        let img = Image::from_argb(3, 2, &[0xFF00_0000, 0xFFFF_FFFF, 0x8012_3456, 0, 1, 2]);
+        for format in [Format::Png, Format::Bmp, Format::Qoi] {
+            let data = encode(&img, format).unwrap();
+            assert_eq!(Format::detect(&data), Some(format));
+            assert_eq!(decode(&data).unwrap(), img, "{:?}", format);
+        }
+        assert_eq!(decode(b"nope"), Err(ImageError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_checked_image_limits() {
+        assert!(checked_image(1, 1).is_ok());
+        assert_eq!(checked_image(0, 1), Err(ImageError::InvalidDimensions));
+        assert_eq!(
+            checked_image(MAX_DIMENSION + 1, 1),
+            Err(ImageError::InvalidDimensions)
+        );
+    }
+}
@@ -1,21 +1,22 @@
-//! PNG decoder with full DEFLATE/zlib decompression.
+//! PNG decoder with full DEFLATE/zlib decompression, plus an encoder.
 //!
 //! Supports all critical chunks (IHDR, PLTE, IDAT, IEND), ancillary chunks
 //! (tRNS, gAMA), all 5 filter types, Adam7 interlacing, color types
 //! 0/2/3/4/6, and bit depths 1/2/4/8/16.
-
-#![allow(dead_code)]
+//!
+//! The encoder writes 8-bit RGB or RGBA images compressed with
+//! fixed-Huffman DEFLATE (used for screenshots).
 
 use alloc::vec::Vec;
 
-use super::{read_be_u16, read_be_u32, DecodedImage, ImageCodecError};
+use crate::{read_be_u16, read_be_u32, Image, ImageError};
 
 // ============================================================================
 // PNG DECODER
 // ============================================================================
 
 /// PNG 8-byte signature.
-pub(crate) const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+pub const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
 /// PNG color types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,14 +29,14 @@ pub enum PngColorType {
 }
 
 impl PngColorType {
-    fn from_u8(v: u8) -> Result<Self, ImageCodecError> {
+    fn from_u8(v: u8) -> Result<Self, ImageError> {
         match v {
             0 => Ok(Self::Grayscale),
             2 => Ok(Self::Rgb),
             3 => Ok(Self::Indexed),
             4 => Ok(Self::GrayscaleAlpha),
             6 => Ok(Self::Rgba),
-            _ => Err(ImageCodecError::Unsupported),
+            _ => Err(ImageError::Unsupported),
         }
     }
 
@@ -62,10 +63,10 @@ struct PngIhdr {
 }
 
 /// Decode a PNG image from raw file data.
-pub fn decode_png(data: &[u8]) -> Result<DecodedImage, ImageCodecError> {
+pub fn decode_png(data: &[u8]) -> Result<Image, ImageError> {
     // Verify signature
-    if data.len() < 8 || data[..8] != PNG_SIGNATURE {
-        return Err(ImageCodecError::InvalidSignature);
+    if data.len() < 8 || data[..8] != SIGNATURE {
+        return Err(ImageError::InvalidSignature);
     }
 
     // Parse chunks
@@ -90,7 +91,7 @@ pub fn decode_png(data: &[u8]) -> Result<DecodedImage, ImageCodecError> {
         match chunk_type {
             b"IHDR" => {
                 if chunk_len < 13 {
-                    return Err(ImageCodecError::CorruptData);
+                    return Err(ImageError::CorruptData);
                 }
                 let cd = &data[chunk_data_start..chunk_end];
                 ihdr = Some(PngIhdr {
@@ -103,7 +104,7 @@ pub fn decode_png(data: &[u8]) -> Result<DecodedImage, ImageCodecError> {
             }
             b"PLTE" => {
                 if !chunk_len.is_multiple_of(3) {
-                    return Err(ImageCodecError::CorruptData);
+                    return Err(ImageError::CorruptData);
                 }
                 let cd = &data[chunk_data_start..chunk_end];
                 palette.clear();
@@ -135,26 +136,26 @@ pub fn decode_png(data: &[u8]) -> Result<DecodedImage, ImageCodecError> {
         pos = chunk_end + 4; // skip CRC
     }
 
-    let ihdr = ihdr.ok_or(ImageCodecError::CorruptData)?;
+    let ihdr = ihdr.ok_or(ImageError::CorruptData)?;
     if ihdr.width == 0 || ihdr.height == 0 {
-        return Err(ImageCodecError::InvalidDimensions);
+        return Err(ImageError::InvalidDimensions);
     }
 
     // Validate bit depth for color type
     match ihdr.color_type {
         PngColorType::Grayscale => {
             if !matches!(ihdr.bit_depth, 1 | 2 | 4 | 8 | 16) {
-                return Err(ImageCodecError::Unsupported);
+                return Err(ImageError::Unsupported);
             }
         }
         PngColorType::Rgb | PngColorType::GrayscaleAlpha | PngColorType::Rgba => {
             if !matches!(ihdr.bit_depth, 8 | 16) {
-                return Err(ImageCodecError::Unsupported);
+                return Err(ImageError::Unsupported);
             }
         }
         PngColorType::Indexed => {
             if !matches!(ihdr.bit_depth, 1 | 2 | 4 | 8) {
-                return Err(ImageCodecError::Unsupported);
+                return Err(ImageError::Unsupported);
             }
         }
     }
@@ -176,7 +177,7 @@ fn decode_png_non_interlaced(
     raw: &[u8],
     palette: &[(u8, u8, u8)],
     trns: &[u8],
-) -> Result<DecodedImage, ImageCodecError> {
+) -> Result<Image, ImageError> {
     let w = ihdr.width as usize;
     let h = ihdr.height as usize;
     let channels = ihdr.color_type.channels();
@@ -184,19 +185,19 @@ fn decode_png_non_interlaced(
     let bytes_per_row = (w * bits_per_pixel).div_ceil(8);
     let bpp_bytes = bits_per_pixel.div_ceil(8); // filter byte stride
 
-    let mut img = DecodedImage::new(ihdr.width, ihdr.height);
+    let mut img = Image::new(ihdr.width, ihdr.height);
     let mut prev_row: Vec<u8> = alloc::vec![0u8; bytes_per_row];
     let mut pos: usize = 0;
 
     for y in 0..h {
         if pos >= raw.len() {
-            return Err(ImageCodecError::TruncatedData);
+            return Err(ImageError::TruncatedData);
         }
         let filter = raw[pos];
         pos += 1;
 
         if pos + bytes_per_row > raw.len() {
-            return Err(ImageCodecError::TruncatedData);
+            return Err(ImageError::TruncatedData);
         }
 
         let mut current_row: Vec<u8> = raw[pos..pos + bytes_per_row].to_vec();
@@ -229,7 +230,7 @@ fn decode_png_interlaced(
     raw: &[u8],
     palette: &[(u8, u8, u8)],
     trns: &[u8],
-) -> Result<DecodedImage, ImageCodecError> {
+) -> Result<Image, ImageError> {
     let w = ihdr.width as usize;
     let h = ihdr.height as usize;
     let channels = ihdr.color_type.channels();
@@ -246,7 +247,7 @@ fn decode_png_interlaced(
         (0, 1, 1, 2),
     ];
 
-    let mut img = DecodedImage::new(ihdr.width, ihdr.height);
+    let mut img = Image::new(ihdr.width, ihdr.height);
     let mut pos: usize = 0;
 
     for &(x_start, y_start, x_step, y_step) in &ADAM7 {
@@ -271,13 +272,13 @@ fn decode_png_interlaced(
 
         for pass_y in 0..pass_h {
             if pos >= raw.len() {
-                return Err(ImageCodecError::TruncatedData);
+                return Err(ImageError::TruncatedData);
             }
             let filter = raw[pos];
             pos += 1;
 
             if pos + bytes_per_row > raw.len() {
-                return Err(ImageCodecError::TruncatedData);
+                return Err(ImageError::TruncatedData);
             }
 
             let mut current_row: Vec<u8> = raw[pos..pos + bytes_per_row].to_vec();
@@ -308,12 +309,7 @@ fn decode_png_interlaced(
 }
 
 /// PNG filter reconstruction (RFC 2083 Section 9).
-fn png_unfilter(
-    filter: u8,
-    current: &mut [u8],
-    prev: &[u8],
-    bpp: usize,
-) -> Result<(), ImageCodecError> {
+fn png_unfilter(filter: u8, current: &mut [u8], prev: &[u8], bpp: usize) -> Result<(), ImageError> {
     let len = current.len();
     match filter {
         0 => {} // None
@@ -346,7 +342,7 @@ fn png_unfilter(
                 current[i] = current[i].wrapping_add(paeth_predictor(a, b, c) as u8);
             }
         }
-        _ => return Err(ImageCodecError::CorruptData),
+        _ => return Err(ImageError::CorruptData),
     }
     Ok(())
 }
@@ -368,12 +364,13 @@ fn paeth_predictor(a: i32, b: i32, c: i32) -> i32 {
 }
 
 /// Convert a full PNG scanline to RGBA pixels in the output image.
+#[allow(clippy::too_many_arguments)]
 fn png_scanline_to_rgba(
     row: &[u8],
     ihdr: &PngIhdr,
     palette: &[(u8, u8, u8)],
     trns: &[u8],
-    img: &mut DecodedImage,
+    img: &mut Image,
     y: u32,
     x_start: u32,
     count: u32,
@@ -563,9 +560,9 @@ fn scale_to_8bit(val: usize, bit_depth: usize) -> u8 {
 // ============================================================================
 
 /// Decompress zlib-wrapped data (CMF + FLG + compressed blocks + Adler-32).
-fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ImageCodecError> {
+fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ImageError> {
     if data.len() < 6 {
-        return Err(ImageCodecError::TruncatedData);
+        return Err(ImageError::TruncatedData);
     }
 
     let cmf = data[0];
@@ -573,18 +570,18 @@ fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ImageCodecError> {
 
     // CMF: bits 0-3 = CM (must be 8 for deflate), bits 4-7 = CINFO
     if cmf & 0x0F != 8 {
-        return Err(ImageCodecError::Unsupported);
+        return Err(ImageError::Unsupported);
     }
 
     // Verify CMF/FLG check
     let check = (cmf as u16) * 256 + (_flg as u16);
     if !check.is_multiple_of(31) {
-        return Err(ImageCodecError::CorruptData);
+        return Err(ImageError::CorruptData);
     }
 
     // Check FDICT flag (bit 5 of FLG) -- we don't support preset dictionaries
     if _flg & 0x20 != 0 {
-        return Err(ImageCodecError::Unsupported);
+        return Err(ImageError::Unsupported);
     }
 
     // Decompress DEFLATE stream starting at offset 2
@@ -599,7 +596,7 @@ fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ImageCodecError> {
         if stored_adler != computed_adler {
             // Some PNG encoders produce valid images with wrong checksums;
             // we log but don't fail for robustness.
-            // return Err(ImageCodecError::ChecksumMismatch);
+            // return Err(ImageError::ChecksumMismatch);
         }
     }
 
@@ -636,13 +633,13 @@ impl<'a> BitReader<'a> {
     }
 
     /// Read `n` bits (up to 25), LSB first.
-    fn read_bits(&mut self, n: u8) -> Result<u32, ImageCodecError> {
+    fn read_bits(&mut self, n: u8) -> Result<u32, ImageError> {
         let mut result: u32 = 0;
         let mut bits_read: u8 = 0;
 
         while bits_read < n {
             if self.byte_pos >= self.data.len() {
-                return Err(ImageCodecError::TruncatedData);
+                return Err(ImageError::TruncatedData);
             }
 
             let available = 8 - self.bit_pos;
@@ -668,7 +665,7 @@ impl<'a> BitReader<'a> {
     }
 
     /// Read a single bit.
-    fn read_bit(&mut self) -> Result<u32, ImageCodecError> {
+    fn read_bit(&mut self) -> Result<u32, ImageError> {
         self.read_bits(1)
     }
 
@@ -681,9 +678,9 @@ impl<'a> BitReader<'a> {
     }
 
     /// Read a byte (must be byte-aligned).
-    fn read_byte(&mut self) -> Result<u8, ImageCodecError> {
+    fn read_byte(&mut self) -> Result<u8, ImageError> {
         if self.byte_pos >= self.data.len() {
-            return Err(ImageCodecError::TruncatedData);
+            return Err(ImageError::TruncatedData);
         }
         let b = self.data[self.byte_pos];
         self.byte_pos += 1;
@@ -692,7 +689,7 @@ impl<'a> BitReader<'a> {
 }
 
 /// DEFLATE decompression (RFC 1951).
-fn deflate_decompress(data: &[u8]) -> Result<Vec<u8>, ImageCodecError> {
+fn deflate_decompress(data: &[u8]) -> Result<Vec<u8>, ImageError> {
     let mut reader = BitReader::new(data);
     let mut output: Vec<u8> = Vec::new();
 
@@ -720,7 +717,7 @@ fn deflate_decompress(data: &[u8]) -> Result<Vec<u8>, ImageCodecError> {
                 let (lit_tree, dist_tree) = decode_dynamic_huffman(&mut reader)?;
                 inflate_block(&mut reader, &lit_tree, &dist_tree, &mut output)?;
             }
-            _ => return Err(ImageCodecError::CorruptData),
+            _ => return Err(ImageError::CorruptData),
         }
 
         if bfinal != 0 {
@@ -734,10 +731,9 @@ fn deflate_decompress(data: &[u8]) -> Result<Vec<u8>, ImageCodecError> {
 /// A Huffman tree for DEFLATE decoding, stored as a lookup table.
 /// Maximum code length in DEFLATE is 15 bits.
 struct HuffmanTree {
-    /// For each code length, the number of codes and starting values.
-    /// Stored as (min_code, symbols) per bit length.
-    /// We use a simple linear decode approach.
-    counts: [u16; 16],
+    /// Canonical decoding tables: for each code length, the first and last
+    /// code and the offset of its first symbol. We use a simple linear
+    /// decode approach.
     symbols: Vec<u16>,
     min_codes: [u32; 16],
     max_codes: [i32; 16],
@@ -746,7 +742,7 @@ struct HuffmanTree {
 
 impl HuffmanTree {
     /// Build a Huffman tree from a list of code lengths.
-    fn from_lengths(lengths: &[u8]) -> Result<Self, ImageCodecError> {
+    fn from_lengths(lengths: &[u8]) -> Result<Self, ImageError> {
         let mut counts = [0u16; 16];
         let mut max_len: usize = 0;
 
@@ -755,7 +751,7 @@ impl HuffmanTree {
             let l = len as usize;
             if l > 0 {
                 if l > 15 {
-                    return Err(ImageCodecError::InvalidHuffmanTable);
+                    return Err(ImageError::InvalidHuffmanTable);
                 }
                 counts[l] += 1;
                 if l > max_len {
@@ -801,7 +797,6 @@ impl HuffmanTree {
         }
 
         Ok(Self {
-            counts,
             symbols,
             min_codes,
             max_codes,
@@ -810,7 +805,7 @@ impl HuffmanTree {
     }
 
     /// Decode one symbol from the bit stream.
-    fn decode(&self, reader: &mut BitReader) -> Result<u16, ImageCodecError> {
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ImageError> {
         let mut code: u32 = 0;
 
         for bits in 1..16u8 {
@@ -824,7 +819,7 @@ impl HuffmanTree {
             }
         }
 
-        Err(ImageCodecError::InvalidHuffmanTable)
+        Err(ImageError::InvalidHuffmanTable)
     }
 }
 
@@ -843,14 +838,12 @@ fn build_fixed_huffman_trees() -> (HuffmanTree, HuffmanTree) {
 
     (
         HuffmanTree::from_lengths(&lit_lengths).unwrap_or_else(|_| HuffmanTree {
-            counts: [0; 16],
             symbols: Vec::new(),
             min_codes: [0; 16],
             max_codes: [-1; 16],
             offsets: [0; 16],
         }),
         HuffmanTree::from_lengths(&dist_lengths).unwrap_or_else(|_| HuffmanTree {
-            counts: [0; 16],
             symbols: Vec::new(),
             min_codes: [0; 16],
             max_codes: [-1; 16],
@@ -862,7 +855,7 @@ fn build_fixed_huffman_trees() -> (HuffmanTree, HuffmanTree) {
 /// Decode dynamic Huffman trees from DEFLATE block type 2 header.
 fn decode_dynamic_huffman(
     reader: &mut BitReader,
-) -> Result<(HuffmanTree, HuffmanTree), ImageCodecError> {
+) -> Result<(HuffmanTree, HuffmanTree), ImageError> {
     let hlit = reader.read_bits(5)? as usize + 257;
     let hdist = reader.read_bits(5)? as usize + 1;
     let hclen = reader.read_bits(4)? as usize + 4;
@@ -921,7 +914,7 @@ fn decode_dynamic_huffman(
                     }
                 }
             }
-            _ => return Err(ImageCodecError::InvalidHuffmanTable),
+            _ => return Err(ImageError::InvalidHuffmanTable),
         }
     }
 
@@ -956,7 +949,7 @@ fn inflate_block(
     lit_tree: &HuffmanTree,
     dist_tree: &HuffmanTree,
     output: &mut Vec<u8>,
-) -> Result<(), ImageCodecError> {
+) -> Result<(), ImageError> {
     loop {
         let sym = lit_tree.decode(reader)?;
 
@@ -970,20 +963,20 @@ fn inflate_block(
             // Length/distance pair
             let len_idx = (sym - 257) as usize;
             if len_idx >= LENGTH_BASE.len() {
-                return Err(ImageCodecError::CorruptData);
+                return Err(ImageError::CorruptData);
             }
             let length =
                 LENGTH_BASE[len_idx] as usize + reader.read_bits(LENGTH_EXTRA[len_idx])? as usize;
 
             let dist_sym = dist_tree.decode(reader)? as usize;
             if dist_sym >= DIST_BASE.len() {
-                return Err(ImageCodecError::CorruptData);
+                return Err(ImageError::CorruptData);
             }
             let distance =
                 DIST_BASE[dist_sym] as usize + reader.read_bits(DIST_EXTRA[dist_sym])? as usize;
 
             if distance > output.len() {
-                return Err(ImageCodecError::CorruptData);
+                return Err(ImageError::CorruptData);
             }
 
             // Copy from back-reference (byte-by-byte for overlapping copies)
@@ -998,6 +991,274 @@ fn inflate_block(
     Ok(())
 }
 
+// ============================================================================
+// PNG ENCODER
+// ============================================================================
+
+/// Longest back-reference DEFLATE can express.
+const MAX_MATCH: usize = 258;
+/// Shortest back-reference worth emitting.
+const MIN_MATCH: usize = 3;
+/// DEFLATE sliding window size.
+const WINDOW_SIZE: usize = 32768;
+/// log2 of the LZ77 hash table size.
+const HASH_BITS: u32 = 15;
+/// Candidates examined per position; bounds the cost on repetitive data.
+const MAX_CHAIN: usize = 32;
+
+/// Encode an RGBA8888 image as an 8-bit PNG.
+///
+/// Fully opaque images are written as RGB, anything else as RGBA. Each
+/// scanline uses the filter with the smallest sum of absolute residuals.
+pub fn encode_png(image: &Image) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = (image.width as usize, image.height as usize);
+    if width == 0 || height == 0 || image.pixels.len() != width * height * 4 {
+        return Err(ImageError::InvalidDimensions);
+    }
+
+    let opaque = image.pixels.chunks_exact(4).all(|p| p[3] == 0xFF);
+    let (color_type, bpp) = if opaque {
+        (PngColorType::Rgb, 3)
+    } else {
+        (PngColorType::Rgba, 4)
+    };
+
+    // Filtered scanlines: one filter-type byte followed by the residuals
+    let stride = width * bpp;
+    let mut raw = Vec::with_capacity((stride + 1) * height);
+    let mut prev = alloc::vec![0u8; stride];
+    let mut cur = Vec::with_capacity(stride);
+    let mut best = Vec::with_capacity(stride);
+    let mut candidate = Vec::with_capacity(stride);
+    for row in image.pixels.chunks_exact(width * 4) {
+        cur.clear();
+        for px in row.chunks_exact(4) {
+            cur.extend_from_slice(&px[..bpp]);
+        }
+
+        let mut best_filter = 0;
+        let mut best_cost = usize::MAX;
+        for filter in 0..5u8 {
+            png_filter(filter, &cur, &prev, bpp, &mut candidate);
+            let cost: usize = candidate
+                .iter()
+                .map(|&b| (b as i8).unsigned_abs() as usize)
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_filter = filter;
+                core::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        raw.push(best_filter);
+        raw.extend_from_slice(&best);
+        core::mem::swap(&mut prev, &mut cur);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&image.width.to_be_bytes());
+    ihdr.extend_from_slice(&image.height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, color_type as u8, 0, 0, 0]);
+
+    let idat = zlib_compress(&raw);
+    let mut out = Vec::with_capacity(idat.len() + 64);
+    out.extend_from_slice(&SIGNATURE);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &idat);
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+/// Apply PNG filter `filter` to `row` (the inverse of [`png_unfilter`]).
+fn png_filter(filter: u8, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    out.clear();
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => paeth_predictor(a as i32, b as i32, c as i32) as u8,
+        };
+        out.push(row[i].wrapping_sub(predicted));
+    }
+}
+
+/// Append a chunk (length, type, data, CRC) to `out`.
+fn write_chunk(out: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let crc_start = out.len();
+    out.extend_from_slice(chunk_type);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[crc_start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// CRC-32 (IEEE 802.3) as used by PNG chunks.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Wrap `data` in a zlib stream compressed with fixed-Huffman DEFLATE.
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    // CMF: deflate with a 32K window; FLG: fastest level, check bits
+    writer.out.extend_from_slice(&[0x78, 0x01]);
+    deflate_fixed(data, &mut writer);
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// LSB-first bit writer for DEFLATE output.
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Append the low `n` bits of `value`, least significant first.
+    fn write_bits(&mut self, value: u32, n: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Append a Huffman code, which DEFLATE stores most significant bit
+    /// first.
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write_bits(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Write a literal/length symbol with the fixed Huffman code (RFC 1951
+/// 3.2.6).
+fn write_fixed_litlen(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + symbol - 280, 8),
+    }
+}
+
+/// Write a back-reference of `len` bytes, `dist` bytes back.
+fn write_match(writer: &mut BitWriter, len: usize, dist: usize) {
+    let li = LENGTH_BASE
+        .iter()
+        .rposition(|&b| b as usize <= len)
+        .unwrap_or(0);
+    write_fixed_litlen(writer, 257 + li as u32);
+    writer.write_bits(
+        (len - LENGTH_BASE[li] as usize) as u32,
+        LENGTH_EXTRA[li] as u32,
+    );
+
+    let di = DIST_BASE
+        .iter()
+        .rposition(|&b| b as usize <= dist)
+        .unwrap_or(0);
+    writer.write_code(di as u32, 5);
+    writer.write_bits(
+        (dist - DIST_BASE[di] as usize) as u32,
+        DIST_EXTRA[di] as u32,
+    );
+}
+
+/// Compress `data` as a single fixed-Huffman DEFLATE block.
+///
+/// Matches are found through hash chains over 3-byte prefixes, which is
+/// plenty for the long runs of identical pixels in screenshots.
+fn deflate_fixed(data: &[u8], writer: &mut BitWriter) {
+    // BFINAL = 1, BTYPE = 01 (fixed Huffman)
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    const NONE: u32 = u32::MAX;
+    let mut head = alloc::vec![NONE; 1 << HASH_BITS];
+    let mut chain = alloc::vec![NONE; WINDOW_SIZE];
+    let hash = |pos: usize| {
+        let v = (data[pos] as u32) << 16 | (data[pos + 1] as u32) << 8 | data[pos + 2] as u32;
+        (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+    };
+    let insert = |pos: usize, head: &mut [u32], chain: &mut [u32]| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(pos);
+            chain[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos as u32;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if pos + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(pos)];
+            let mut tries = 0;
+            while candidate != NONE && tries < MAX_CHAIN {
+                let start = candidate as usize;
+                let dist = pos - start;
+                if dist > WINDOW_SIZE {
+                    break;
+                }
+                let len = data[start..start + max_len]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = dist;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                candidate = chain[start % WINDOW_SIZE];
+                tries += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(writer, best_len, best_dist);
+            for p in pos..pos + best_len {
+                insert(p, &mut head, &mut chain);
+            }
+            pos += best_len;
+        } else {
+            write_fixed_litlen(writer, data[pos] as u32);
+            insert(pos, &mut head, &mut chain);
+            pos += 1;
+        }
+    }
+
+    write_fixed_litlen(writer, 256); // end of block
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -1012,13 +1273,13 @@ mod tests {
     #[test]
     fn test_png_signature_check() {
         let bad = [0u8; 8];
-        assert_eq!(decode_png(&bad), Err(ImageCodecError::InvalidSignature));
+        assert_eq!(decode_png(&bad), Err(ImageError::InvalidSignature));
     }
 
     #[test]
     fn test_png_too_short() {
         let data = [137, 80, 78, 71];
-        assert_eq!(decode_png(&data), Err(ImageCodecError::InvalidSignature));
+        assert_eq!(decode_png(&data), Err(ImageError::InvalidSignature));
     }
 
     #[test]
@@ -1028,7 +1289,7 @@ mod tests {
         assert_eq!(PngColorType::from_u8(3), Ok(PngColorType::Indexed));
         assert_eq!(PngColorType::from_u8(4), Ok(PngColorType::GrayscaleAlpha));
         assert_eq!(PngColorType::from_u8(6), Ok(PngColorType::Rgba));
-        assert_eq!(PngColorType::from_u8(7), Err(ImageCodecError::Unsupported));
+        assert_eq!(PngColorType::from_u8(7), Err(ImageError::Unsupported));
     }
 
     #[test]
@@ -1132,7 +1393,7 @@ mod tests {
         let prev = vec![0, 0, 0];
         assert_eq!(
             png_unfilter(5, &mut row, &prev, 1),
-            Err(ImageCodecError::CorruptData)
+            Err(ImageError::CorruptData)
         );
     }
 
@@ -1161,7 +1422,7 @@ mod tests {
         // Simple tree: symbols 0,1 with lengths 1,1 => codes 0,1
         let lengths = [1u8, 1];
         let tree = HuffmanTree::from_lengths(&lengths).unwrap();
-        assert_eq!(tree.counts[1], 2);
+        assert_eq!(tree.max_codes[1], 1);
         assert_eq!(tree.symbols.len(), 2);
     }
 
@@ -1169,23 +1430,75 @@ mod tests {
     fn test_deflate_stored_block() {
         // Construct a minimal stored block: BFINAL=1, BTYPE=00, LEN=3, NLEN=~3,
         // data="abc"
-        let mut block = Vec::new();
-        block.push(0x01); // BFINAL=1, BTYPE=00 (stored) => bits: 1 00 = 0b001
-                          // LEN = 3 (LE)
-        block.push(0x03);
-        block.push(0x00);
-        // NLEN = !3 = 0xFFFC (LE)
-        block.push(0xFC);
-        block.push(0xFF);
-        // Data
-        block.push(b'a');
-        block.push(b'b');
-        block.push(b'c');
+        let block = vec![
+            0x01, // BFINAL=1, BTYPE=00 (stored) => bits: 1 00 = 0b001
+            0x03, 0x00, // LEN = 3 (LE)
+            0xFC, 0xFF, // NLEN = !3 = 0xFFFC (LE)
+            b'a', b'b', b'c',
+        ];
 
         let result = deflate_decompress(&block).unwrap();
         assert_eq!(result, b"abc");
     }
 
+    // -----------------------------------------------------------------------
+    // Encoder tests
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_crc32_known() {
+        // crc32("IEND") is the CRC every PNG ends with
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn test_deflate_fixed_roundtrip() {
+        let mut data = Vec::new();
+        for i in 0..2000u32 {
+            data.push((i % 7) as u8);
+            data.push((i * 31 / 13) as u8);
+        }
+        data.extend_from_slice(&[0xAB; 600]);
+        let mut writer = BitWriter::default();
+        deflate_fixed(&data, &mut writer);
+        let compressed = writer.finish();
+        assert!(compressed.len() < data.len() / 2);
+        assert_eq!(deflate_decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_encode_png_roundtrip_rgb() {
+        let mut image = Image::new(37, 23);
+        for y in 0..23 {
+            for x in 0..37 {
+                image.set_pixel(x, y, (x * 7) as u8, (y * 11) as u8, (x ^ y) as u8, 0xFF);
+            }
+        }
+        let png = encode_png(&image).unwrap();
+        assert_eq!(png[25], PngColorType::Rgb as u8); // IHDR color type
+        assert_eq!(decode_png(&png).unwrap(), image);
+    }
+
+    #[test]
+    fn test_encode_png_roundtrip_rgba() {
+        let mut image = Image::new(4, 3);
+        image.set_pixel(1, 1, 10, 20, 30, 40);
+        let png = encode_png(&image).unwrap();
+        assert_eq!(png[25], PngColorType::Rgba as u8);
+        assert_eq!(decode_png(&png).unwrap(), image);
+    }
+
+    #[test]
+    fn test_encode_png_rejects_bad_dimensions() {
+        let mut image = Image::new(2, 2);
+        image.pixels.pop();
+        assert_eq!(encode_png(&image), Err(ImageError::InvalidDimensions));
+        assert_eq!(
+            encode_png(&Image::new(0, 5)),
+            Err(ImageError::InvalidDimensions)
+        );
+    }
+
     #[test]
     fn test_bit_reader_basic() {
         let data = [0b10110100, 0xFF];
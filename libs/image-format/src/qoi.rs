@@ -0,0 +1,242 @@
+//! QOI ("Quite OK Image") decoder and encoder.
+//!
+//! Specification: <https://qoiformat.org/qoi-specification.pdf>. A 14-byte
+//! header ("qoif", big-endian width and height, channels, colorspace) is
+//! followed by a stream of chunks and an end marker of seven zero bytes
+//! and a one. The encoder writes 3 channels for opaque images, 4 otherwise.
+
+use alloc::vec::Vec;
+
+use crate::{checked_image, read_be_u32, Image, ImageError};
+
+/// "qoif" file signature.
+pub const SIGNATURE: [u8; 4] = *b"qoif";
+
+const HEADER_SIZE: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+const OP_INDEX: u8 = 0x00; // 00xxxxxx
+const OP_DIFF: u8 = 0x40; // 01xxxxxx
+const OP_LUMA: u8 = 0x80; // 10xxxxxx
+const OP_RUN: u8 = 0xC0; // 11xxxxxx
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const TAG_MASK: u8 = 0xC0;
+
+/// Longest run a single `OP_RUN` chunk can hold.
+const MAX_RUN: u8 = 62;
+
+/// Index position of a pixel in the 64-entry color cache.
+#[inline]
+fn hash([r, g, b, a]: [u8; 4]) -> usize {
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+/// Decode a QOI file.
+pub fn decode_qoi(data: &[u8]) -> Result<Image, ImageError> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(ImageError::InvalidSignature);
+    }
+    if data.len() < HEADER_SIZE + END_MARKER.len() {
+        return Err(ImageError::TruncatedData);
+    }
+    let channels = data[12];
+    if channels != 3 && channels != 4 {
+        return Err(ImageError::Unsupported);
+    }
+    let mut image = checked_image(read_be_u32(data, 4), read_be_u32(data, 8))?;
+
+    let chunks = &data[HEADER_SIZE..data.len() - END_MARKER.len()];
+    let mut index = [[0u8; 4]; 64];
+    let mut px = [0, 0, 0, 0xFF];
+    let mut pos = 0;
+    let mut run = 0;
+
+    for out in image.pixels.chunks_exact_mut(4) {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let b1 = *chunks.get(pos).ok_or(ImageError::TruncatedData)?;
+            pos += 1;
+            match b1 {
+                OP_RGB | OP_RGBA => {
+                    let n = if b1 == OP_RGB { 3 } else { 4 };
+                    let bytes = chunks.get(pos..pos + n).ok_or(ImageError::TruncatedData)?;
+                    px[..n].copy_from_slice(bytes);
+                    pos += n;
+                }
+                _ => match b1 & TAG_MASK {
+                    OP_INDEX => px = index[b1 as usize],
+                    OP_DIFF => {
+                        px[0] = px[0].wrapping_add((b1 >> 4) & 0x03).wrapping_sub(2);
+                        px[1] = px[1].wrapping_add((b1 >> 2) & 0x03).wrapping_sub(2);
+                        px[2] = px[2].wrapping_add(b1 & 0x03).wrapping_sub(2);
+                    }
+                    OP_LUMA => {
+                        let b2 = *chunks.get(pos).ok_or(ImageError::TruncatedData)?;
+                        pos += 1;
+                        let dg = (b1 & 0x3F).wrapping_sub(32);
+                        px[0] = px[0].wrapping_add(dg.wrapping_sub(8).wrapping_add(b2 >> 4));
+                        px[1] = px[1].wrapping_add(dg);
+                        px[2] = px[2].wrapping_add(dg.wrapping_sub(8).wrapping_add(b2 & 0x0F));
+                    }
+                    _ => run = b1 & 0x3F, // OP_RUN: this pixel plus `run` more
+                },
+            }
+            index[hash(px)] = px;
+        }
+        out.copy_from_slice(&px);
+    }
+    Ok(image)
+}
+
+/// Encode as QOI (sRGB colorspace).
+pub fn encode_qoi(image: &Image) -> Result<Vec<u8>, ImageError> {
+    let count = (image.width as usize) * (image.height as usize);
+    if image.width == 0 || image.height == 0 || image.pixels.len() != count * 4 {
+        return Err(ImageError::InvalidDimensions);
+    }
+    let channels = if image.is_opaque() { 3 } else { 4 };
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + count + END_MARKER.len());
+    out.extend_from_slice(&SIGNATURE);
+    out.extend_from_slice(&image.width.to_be_bytes());
+    out.extend_from_slice(&image.height.to_be_bytes());
+    out.push(channels);
+    out.push(0); // sRGB with linear alpha
+
+    let mut index = [[0u8; 4]; 64];
+    let mut prev = [0, 0, 0, 0xFF];
+    let mut run = 0u8;
+
+    for chunk in image.pixels.chunks_exact(4) {
+        let px = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        if px == prev {
+            run += 1;
+            if run == MAX_RUN {
+                out.push(OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+        if run > 0 {
+            out.push(OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let slot = hash(px);
+        if index[slot] == px {
+            out.push(OP_INDEX | slot as u8);
+        } else {
+            index[slot] = px;
+            if px[3] != prev[3] {
+                out.push(OP_RGBA);
+                out.extend_from_slice(&px);
+            } else {
+                let dr = px[0].wrapping_sub(prev[0]) as i8;
+                let dg = px[1].wrapping_sub(prev[1]) as i8;
+                let db = px[2].wrapping_sub(prev[2]) as i8;
+                let dr_dg = dr.wrapping_sub(dg);
+                let db_dg = db.wrapping_sub(dg);
+                if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                    out.push(
+                        OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8,
+                    );
+                } else if (-32..=31).contains(&dg)
+                    && (-8..=7).contains(&dr_dg)
+                    && (-8..=7).contains(&db_dg)
+                {
+                    out.push(OP_LUMA | (dg + 32) as u8);
+                    out.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    out.push(OP_RGB);
+                    out.extend_from_slice(&px[..3]);
+                }
+            }
+        }
+        prev = px;
+    }
+    if run > 0 {
+        out.push(OP_RUN | (run - 1));
+    }
+    out.extend_from_slice(&END_MARKER);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn gradient(width: u32, height: u32, alpha: bool) -> Image {
+        let mut img = Image::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let a = if alpha { (x * 7) as u8 } else { 0xFF };
+                img.set_pixel(x, y, (x * 3) as u8, (y * 5) as u8, (x ^ y) as u8, a);
+            }
+        }
+        img
+    }
+
+    #[test]
+    fn test_roundtrip_opaque() {
+        let img = gradient(37, 11, false);
+        let qoi = encode_qoi(&img).unwrap();
+        assert_eq!(qoi[12], 3);
+        assert!(qoi.ends_with(&END_MARKER));
+        assert_eq!(decode_qoi(&qoi).unwrap(), img);
+    }
+
+    #[test]
+    fn test_roundtrip_alpha_and_runs() {
+        let mut img = gradient(20, 9, true);
+        // A long run crosses the 62-pixel chunk limit.
+        for x in 0..20 {
+            for y in 2..7 {
+                img.set_pixel(x, y, 9, 9, 9, 9);
+            }
+        }
+        let qoi = encode_qoi(&img).unwrap();
+        assert_eq!(qoi[12], 4);
+        assert_eq!(decode_qoi(&qoi).unwrap(), img);
+    }
+
+    #[test]
+    fn test_decode_reference_chunks() {
+        // 4x1: RGB literal, DIFF (+1,-1,0), INDEX back to the first pixel,
+        // then a run of one.
+        let mut data = vec![];
+        data.extend_from_slice(b"qoif");
+        data.extend_from_slice(&4u32.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&[3, 0]);
+        data.extend_from_slice(&[OP_RGB, 10, 20, 30]);
+        data.push(OP_DIFF | (3 << 4) | (1 << 2) | 2);
+        data.push(OP_INDEX | hash([10, 20, 30, 0xFF]) as u8);
+        data.push(OP_RUN);
+        data.extend_from_slice(&END_MARKER);
+
+        let img = decode_qoi(&data).unwrap();
+        assert_eq!(
+            img.pixels,
+            vec![10, 20, 30, 255, 11, 19, 30, 255, 10, 20, 30, 255, 10, 20, 30, 255]
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert_eq!(decode_qoi(b"qoix"), Err(ImageError::InvalidSignature));
+        assert_eq!(decode_qoi(b"qoif\0\0"), Err(ImageError::TruncatedData));
+
+        let mut qoi = encode_qoi(&gradient(4, 4, false)).unwrap();
+        qoi[12] = 5;
+        assert_eq!(decode_qoi(&qoi), Err(ImageError::Unsupported));
+
+        // Header promises more pixels than the stream holds.
+        let mut qoi = encode_qoi(&gradient(4, 4, false)).unwrap();
+        qoi[7] = 200;
+        assert_eq!(decode_qoi(&qoi), Err(ImageError::TruncatedData));
+    }
+}
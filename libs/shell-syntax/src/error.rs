@@ -0,0 +1,28 @@
+//! Error type for lexing, parsing and arithmetic evaluation.
+
+use alloc::string::String;
+use core::fmt;
+
+/// An error in shell input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Syntax error during lexing or parsing.
+    Syntax(String),
+    /// A number in an arithmetic expression is not valid.
+    NotANumber(String),
+    /// Division by zero in arithmetic.
+    DivisionByZero,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(msg) => write!(f, "syntax error: {}", msg),
+            Error::NotANumber(s) => write!(f, "{}: not a valid number", s),
+            Error::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+/// Result type alias for this crate.
+pub type Result<T> = core::result::Result<T, Error>;
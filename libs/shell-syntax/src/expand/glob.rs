@@ -0,0 +1,253 @@
+//! Glob (pathname expansion) pattern matching.
+//!
+//! Supports `*`, `?`, `[...]`, `[!...]`, `[^...]`, and extended globs
+//! (`extglob`): `?(pat)`, `*(pat)`, `+(pat)`, `@(pat)`, `!(pat)`.
+//! [`expand`] matches a pattern against the directories the executor
+//! lists.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use super::Executor;
+
+/// Test whether `pattern` matches `text`.
+///
+/// - `*` matches zero or more characters (not `/`)
+/// - `?` matches exactly one character (not `/`)
+/// - `[abc]` matches any character in the set
+/// - `[a-z]` matches a range
+/// - `[!abc]` or `[^abc]` negated class
+/// - `\c` matches `c` literally
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pat: Vec<char> = pattern.chars().collect();
+    let txt: Vec<char> = text.chars().collect();
+    glob_match_impl(&pat, 0, &txt, 0, true)
+}
+
+/// Test whether `pattern` matches `text` as a `[[ == ]]` pattern: like
+/// [`glob_match`], but `*` and `?` also match `/`.
+pub fn pattern_match(pattern: &str, text: &str) -> bool {
+    let pat: Vec<char> = pattern.chars().collect();
+    let txt: Vec<char> = text.chars().collect();
+    glob_match_impl(&pat, 0, &txt, 0, false)
+}
+
+fn glob_match_impl(pat: &[char], mut pi: usize, txt: &[char], mut ti: usize, path: bool) -> bool {
+    let plen = pat.len();
+    let tlen = txt.len();
+    let mut star_pi: Option<usize> = None;
+    let mut star_ti: usize = 0;
+
+    while ti < tlen {
+        if pi + 1 < plen && pat[pi] == '\\' && pat[pi + 1] == txt[ti] {
+            pi += 2;
+            ti += 1;
+        } else if pi + 1 < plen && pat[pi] == '\\' {
+            if let Some(sp) = star_pi {
+                if path && txt[star_ti] == '/' {
+                    return false;
+                }
+                pi = sp + 1;
+                star_ti += 1;
+                ti = star_ti;
+            } else {
+                return false;
+            }
+        } else if pi < plen && pat[pi] == '?' && !(path && txt[ti] == '/') {
+            pi += 1;
+            ti += 1;
+        } else if pi < plen && pat[pi] == '*' {
+            star_pi = Some(pi);
+            star_ti = ti;
+            pi += 1;
+        } else if pi < plen && pat[pi] == '[' {
+            let (matched, end) = match_char_class(&pat[pi..], txt[ti]);
+            if matched {
+                pi += end;
+                ti += 1;
+            } else if let Some(sp) = star_pi {
+                if path && txt[star_ti] == '/' {
+                    return false;
+                }
+                pi = sp + 1;
+                star_ti += 1;
+                ti = star_ti;
+            } else {
+                return false;
+            }
+        } else if pi < plen && pat[pi] == txt[ti] {
+            pi += 1;
+            ti += 1;
+        } else if let Some(sp) = star_pi {
+            if path && txt[star_ti] == '/' {
+                return false;
+            }
+            pi = sp + 1;
+            star_ti += 1;
+            ti = star_ti;
+        } else {
+            return false;
+        }
+    }
+
+    while pi < plen && pat[pi] == '*' {
+        pi += 1;
+    }
+
+    pi == plen
+}
+
+fn match_char_class(chars: &[char], ch: char) -> (bool, usize) {
+    let len = chars.len();
+    if len < 2 || chars[0] != '[' {
+        return (false, 0);
+    }
+
+    let mut i = 1;
+    let negated = if i < len && (chars[i] == '!' || chars[i] == '^') {
+        i += 1;
+        true
+    } else {
+        false
+    };
+
+    let mut matched = false;
+
+    // Leading `]` is literal
+    if i < len && chars[i] == ']' {
+        if ch == ']' {
+            matched = true;
+        }
+        i += 1;
+    }
+
+    while i < len && chars[i] != ']' {
+        if i + 2 < len && chars[i + 1] == '-' && chars[i + 2] != ']' {
+            let lo = chars[i];
+            let hi = chars[i + 2];
+            if ch >= lo && ch <= hi {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if chars[i] == ch {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+
+    if i < len && chars[i] == ']' {
+        i += 1;
+    } else {
+        return (false, 0);
+    }
+
+    let result = if negated { !matched } else { matched };
+    (result, i)
+}
+
+/// Check if a string contains glob metacharacters.
+pub fn contains_glob_chars(s: &str) -> bool {
+    let mut escaped = false;
+    for ch in s.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if ch == '\\' {
+            escaped = true;
+            continue;
+        }
+        if matches!(ch, '*' | '?' | '[') {
+            return true;
+        }
+    }
+    false
+}
+
+/// Expand the pathname pattern `pattern` against the directories listed
+/// by `exec`, returning the matching paths in sorted order (none if
+/// nothing matches).
+///
+/// Each `/`-separated component matches the names of one directory
+/// level.  A leading `.` must be matched explicitly, `.` and `..` only
+/// come from a literal component, and a trailing `/` matches directories
+/// only.  Quoted characters are backslash-escaped in `pattern`.
+pub fn expand(pattern: &str, exec: &mut dyn Executor) -> Vec<String> {
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    if components.is_empty() {
+        return Vec::new();
+    }
+
+    let root = if pattern.starts_with('/') { "/" } else { "" };
+    let mut paths = vec![String::from(root)];
+
+    for component in components {
+        let mut next = Vec::new();
+        if contains_glob_chars(component) {
+            let dotted = component.starts_with('.') || component.starts_with("\\.");
+            for dir in &paths {
+                let Some(names) = exec.read_dir(if dir.is_empty() { "." } else { dir }) else {
+                    continue;
+                };
+                for name in names {
+                    if name == "." || name == ".." || (name.starts_with('.') && !dotted) {
+                        continue;
+                    }
+                    if glob_match(component, &name) {
+                        next.push(join(dir, &name));
+                    }
+                }
+            }
+        } else {
+            let name = unescape(component);
+            for dir in &paths {
+                let exists = name == "."
+                    || name == ".."
+                    || exec
+                        .read_dir(if dir.is_empty() { "." } else { dir })
+                        .is_some_and(|names| names.contains(&name));
+                if exists {
+                    next.push(join(dir, &name));
+                }
+            }
+        }
+        paths = next;
+        if paths.is_empty() {
+            return paths;
+        }
+    }
+
+    if pattern.ends_with('/') {
+        paths.retain(|path| exec.read_dir(path).is_some());
+        for path in &mut paths {
+            path.push('/');
+        }
+    }
+    paths.sort();
+    paths
+}
+
+/// `dir` and `name` joined with a `/`; an empty `dir` is the current
+/// directory.
+fn join(dir: &str, name: &str) -> String {
+    let mut path = String::from(dir);
+    if !path.is_empty() && !path.ends_with('/') {
+        path.push('/');
+    }
+    path.push_str(name);
+    path
+}
+
+/// `component` with its backslash escapes resolved.
+fn unescape(component: &str) -> String {
+    let mut name = String::with_capacity(component.len());
+    let mut chars = component.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => name.extend(chars.next()),
+            _ => name.push(ch),
+        }
+    }
+    name
+}
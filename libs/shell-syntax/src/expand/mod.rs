@@ -0,0 +1,353 @@
+//! Word expansion pipeline.
+//!
+//! Implements the Bash expansion order:
+//! 1. Brace expansion
+//! 2. Tilde expansion
+//! 3. Parameter/variable expansion, command substitution, arithmetic expansion
+//!    and process substitution (left to right)
+//! 4. Word splitting
+//! 5. Pathname expansion (globbing)
+//! 6. Quote removal
+//!
+//! Command and process substitution run shell commands, arithmetic
+//! expansion may assign variables, pathname expansion lists directories
+//! and errors must be reported, all of which the expander leaves to the
+//! executor through [`Executor`].
+
+pub mod brace;
+pub mod glob;
+pub mod parameter;
+pub mod regex;
+pub mod tilde;
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use parameter::{Context, SpecialVars};
+
+use crate::parser::word;
+
+/// Runs the commands of `$(cmd)`, `` `cmd` ``, `<(cmd)` and `>(cmd)`, makes
+/// the assignments of `$(( ))`, lists directories for pathname expansion,
+/// and reports expansion errors.
+pub trait Executor {
+    /// Run `cmd` and return its standard output without trailing newlines.
+    fn command(&mut self, cmd: &str) -> String;
+
+    /// Start `cmd` with its standard input (`>(cmd)`, `output` set) or
+    /// output (`<(cmd)`) connected to a pipe, and return a path naming the
+    /// other end of the pipe.
+    fn process(&mut self, cmd: &str, output: bool) -> String;
+
+    /// Assign `value` to the variable `name` (`name[i]` for an array
+    /// element).
+    fn assign(&mut self, name: &str, value: &str);
+
+    /// List the names in the directory `path` (`.` for the current
+    /// directory), or `None` if it cannot be read.
+    fn read_dir(&mut self, path: &str) -> Option<Vec<String>>;
+
+    /// Report an error found while expanding, such as an invalid
+    /// arithmetic expression.
+    fn error(&mut self, msg: &str);
+}
+
+/// Expand a single word through the full expansion pipeline.
+///
+/// Returns one or more words (due to word splitting and brace expansion).
+pub fn expand_word(
+    raw: &str,
+    vars: &BTreeMap<String, String>,
+    special: &SpecialVars,
+    do_glob: bool,
+    exec: &mut dyn Executor,
+) -> Vec<String> {
+    let mut results = expand_fields(raw, vars, special, do_glob, exec);
+
+    // If expansion produced nothing, return a single empty string
+    if results.is_empty() {
+        results.push(String::new());
+    }
+
+    results
+}
+
+/// Expand a word into fields; an unquoted expansion that comes out empty
+/// yields no field at all.
+fn expand_fields(
+    raw: &str,
+    vars: &BTreeMap<String, String>,
+    special: &SpecialVars,
+    do_glob: bool,
+    exec: &mut dyn Executor,
+) -> Vec<String> {
+    // 1. Brace expansion
+    let braced = brace::expand_braces(raw);
+
+    let mut results = Vec::new();
+
+    for w in &braced {
+        // 2. Tilde expansion
+        let tilded = tilde::expand_tilde(w, vars);
+
+        // 3. Parameter expansion (includes command substitution and
+        // arithmetic)
+        let expanded = parameter::expand_parameters(&tilded, vars, special, exec, Context::Word);
+
+        // 4. Word splitting (quoted text is protected)
+        let ifs = vars.get("IFS").map(|s| s.as_str()).unwrap_or(" \t\n");
+        let split = word::word_split(&expanded, ifs);
+
+        for part in &split {
+            // 5. Pathname expansion (globbing); a pattern that matches
+            // nothing is left as it is
+            if do_glob && glob::contains_glob_chars(part) {
+                let matches = glob::expand(&drop_quote_marks(part), exec);
+                if !matches.is_empty() {
+                    results.extend(matches);
+                    continue;
+                }
+            }
+
+            // 6. Quote removal
+            results.push(word::remove_quotes(part));
+        }
+    }
+
+    results
+}
+
+/// Expand a list of words (e.g., command arguments).
+pub fn expand_words(
+    words: &[crate::parser::ast::Word],
+    vars: &BTreeMap<String, String>,
+    special: &SpecialVars,
+    do_glob: bool,
+    exec: &mut dyn Executor,
+) -> Vec<String> {
+    let mut result = Vec::new();
+    for w in words {
+        result.extend(expand_fields(&w.raw, vars, special, do_glob, exec));
+    }
+    result
+}
+
+/// Expand a word to a single string: no brace expansion, word splitting or
+/// globbing (assignment values and `[[ ]]` operands).
+pub fn expand_string(
+    raw: &str,
+    vars: &BTreeMap<String, String>,
+    special: &SpecialVars,
+    exec: &mut dyn Executor,
+) -> String {
+    let tilded = tilde::expand_tilde(raw, vars);
+    let expanded = parameter::expand_parameters(&tilded, vars, special, exec, Context::Word);
+    word::remove_quotes(&expanded)
+}
+
+/// Expand a `[[ ]]` pattern operand (`==`, `!=`, `=~`) to a single string
+/// in which everything that was quoted stays backslash-protected, so that
+/// it matches literally.
+pub fn expand_pattern(
+    raw: &str,
+    vars: &BTreeMap<String, String>,
+    special: &SpecialVars,
+    exec: &mut dyn Executor,
+) -> String {
+    let tilded = tilde::expand_tilde(raw, vars);
+    let expanded = parameter::expand_parameters(&tilded, vars, special, exec, Context::Word);
+    drop_quote_marks(&expanded)
+}
+
+/// Drop the empty-quote markers from a word expanded by
+/// [`parameter::expand_parameters`] but keep the escapes, leaving a
+/// pattern in which quoted text matches literally.
+fn drop_quote_marks(expanded: &str) -> String {
+    let mut pattern = String::with_capacity(expanded.len());
+    let mut chars = expanded.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                pattern.push(ch);
+                if let Some(next) = chars.next() {
+                    pattern.push(next);
+                }
+            }
+            '\'' | '"' => {}
+            _ => pattern.push(ch),
+        }
+    }
+    pattern
+}
+
+/// Expand the body of a here-document whose delimiter was not quoted.
+pub fn expand_heredoc(
+    body: &str,
+    vars: &BTreeMap<String, String>,
+    special: &SpecialVars,
+    exec: &mut dyn Executor,
+) -> String {
+    parameter::expand_parameters(body, vars, special, exec, Context::HereDoc)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::*;
+
+    /// An executor over a fixed directory tree that echoes commands and
+    /// records assignments and errors.
+    #[derive(Default)]
+    struct TestExec {
+        dirs: BTreeMap<&'static str, Vec<&'static str>>,
+        assigned: Vec<(String, String)>,
+        errors: Vec<String>,
+    }
+
+    impl TestExec {
+        fn with_files() -> Self {
+            let mut dirs = BTreeMap::new();
+            dirs.insert(
+                ".",
+                vec!["b.txt", "a.txt", "notes.md", ".hidden.txt", "src"],
+            );
+            dirs.insert("src", vec!["main.rs", "lib.rs", "README"]);
+            dirs.insert("/", vec!["etc", "tmp"]);
+            dirs.insert("/etc", vec!["hosts", "fstab.conf", "vsh.conf"]);
+            dirs.insert("/tmp", vec![]);
+            Self {
+                dirs,
+                ..Self::default()
+            }
+        }
+    }
+
+    impl Executor for TestExec {
+        fn command(&mut self, cmd: &str) -> String {
+            alloc::format!("<{}>", cmd)
+        }
+
+        fn process(&mut self, _cmd: &str, _output: bool) -> String {
+            String::from("/dev/fd/63")
+        }
+
+        fn assign(&mut self, name: &str, value: &str) {
+            self.assigned.push((name.to_owned(), value.to_owned()));
+        }
+
+        fn read_dir(&mut self, path: &str) -> Option<Vec<String>> {
+            let names = self.dirs.get(path)?;
+            Some(names.iter().map(|&n| String::from(n)).collect())
+        }
+
+        fn error(&mut self, msg: &str) {
+            self.errors.push(msg.to_owned());
+        }
+    }
+
+    fn expand(raw: &str, vars: &[(&str, &str)]) -> Vec<String> {
+        let vars = vars
+            .iter()
+            .map(|&(k, v)| (String::from(k), String::from(v)))
+            .collect();
+        expand_word(
+            raw,
+            &vars,
+            &SpecialVars::default(),
+            true,
+            &mut TestExec::with_files(),
+        )
+    }
+
+    #[test]
+    fn test_braces_and_parameters() {
+        assert_eq!(expand("a{b,c}d", &[]), ["abd", "acd"]);
+        assert_eq!(expand("${X:-def}", &[]), ["def"]);
+        assert_eq!(expand("$X", &[("X", "one  two")]), ["one", "two"]);
+        assert_eq!(expand("\"$X\"", &[("X", "one  two")]), ["one  two"]);
+        assert_eq!(expand("$(date)", &[]), ["<date>"]);
+    }
+
+    #[test]
+    fn test_arithmetic_goes_through_executor() {
+        let mut exec = TestExec::default();
+        let words = expand_word(
+            "$((n = 2 + 3))",
+            &BTreeMap::new(),
+            &SpecialVars::default(),
+            true,
+            &mut exec,
+        );
+        assert_eq!(words, ["5"]);
+        assert_eq!(exec.assigned, [(String::from("n"), String::from("5"))]);
+    }
+
+    #[test]
+    fn test_glob_sorted_matches() {
+        assert_eq!(expand("*.txt", &[]), ["a.txt", "b.txt"]);
+        assert_eq!(expand("?.txt", &[]), ["a.txt", "b.txt"]);
+        assert_eq!(expand("[!a].txt", &[]), ["b.txt"]);
+    }
+
+    #[test]
+    fn test_glob_no_match_keeps_word() {
+        assert_eq!(expand("*.rs", &[]), ["*.rs"]);
+        assert_eq!(expand("missing/*", &[]), ["missing/*"]);
+    }
+
+    #[test]
+    fn test_glob_hidden_files_need_a_dot() {
+        assert_eq!(expand(".*.txt", &[]), [".hidden.txt"]);
+        assert!(!expand("*", &[]).contains(&String::from(".hidden.txt")));
+    }
+
+    #[test]
+    fn test_glob_paths() {
+        assert_eq!(expand("src/*.rs", &[]), ["src/lib.rs", "src/main.rs"]);
+        assert_eq!(expand("*/README", &[]), ["src/README"]);
+        assert_eq!(expand("*/main.c", &[]), ["*/main.c"]);
+        assert_eq!(
+            expand("/etc/*.conf", &[]),
+            ["/etc/fstab.conf", "/etc/vsh.conf"]
+        );
+        assert_eq!(expand("/*/", &[]), ["/etc/", "/tmp/"]);
+    }
+
+    #[test]
+    fn test_quoted_glob_is_literal() {
+        assert_eq!(expand("'*.txt'", &[]), ["*.txt"]);
+        assert_eq!(expand("\"*\".txt", &[]), ["*.txt"]);
+        assert_eq!(expand("\\*.txt", &[]), ["*.txt"]);
+        assert_eq!(expand("\"$P\"", &[("P", "*.txt")]), ["*.txt"]);
+    }
+
+    #[test]
+    fn test_unquoted_expansion_is_globbed() {
+        assert_eq!(expand("$P", &[("P", "*.txt")]), ["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn test_noglob() {
+        let words = expand_word(
+            "*.txt",
+            &BTreeMap::new(),
+            &SpecialVars::default(),
+            false,
+            &mut TestExec::with_files(),
+        );
+        assert_eq!(words, ["*.txt"]);
+    }
+
+    #[test]
+    fn test_expand_pattern_keeps_quoted_text_literal() {
+        let pattern = expand_pattern(
+            "\"*\"x*",
+            &BTreeMap::new(),
+            &SpecialVars::default(),
+            &mut TestExec::default(),
+        );
+        assert_eq!(pattern, "\\*x*");
+        assert!(glob::pattern_match(&pattern, "*xyz"));
+        assert!(!glob::pattern_match(&pattern, "axyz"));
+    }
+}
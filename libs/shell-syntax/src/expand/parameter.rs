@@ -1,10 +1,25 @@
 //! Parameter expansion.
 //!
-//! Handles `$VAR`, `${VAR}`, and all the `${VAR...}` operators from Bash.
-
-use alloc::{collections::BTreeMap, format, string::String};
-
-use crate::expand::glob;
+//! Handles `$VAR`, `${VAR}`, and all the `${VAR...}` operators from Bash,
+//! plus the substitutions that share its scanning: `$((expr))`, `$(cmd)`,
+//! `` `cmd` `` and `<(cmd)` / `>(cmd)`.
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+
+use crate::expand::{glob::pattern_match, Executor};
+
+/// What the result of [`expand_parameters`] is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// A command word.  Quotes are removed, and everything that was quoted
+    /// comes back backslash-protected so that field splitting and quote
+    /// removal leave it intact; unquoted expansion results stay open to
+    /// splitting.
+    Word,
+    /// A here-document body.  Quotes are ordinary characters, a backslash
+    /// only escapes `$`, `` ` ``, `\` and newline, and the result is final.
+    HereDoc,
+}
 
 /// Expand parameters/variables in a word.
 ///
@@ -13,58 +28,114 @@ use crate::expand::glob;
 /// `${VAR%%pat}`, `${VAR#pat}`, `${VAR##pat}`, `${VAR/pat/rep}`,
 /// `${VAR//pat/rep}`, `${VAR^pat}`, `${VAR^^pat}`, `${VAR,pat}`,
 /// `${VAR,,pat}`, `${VAR:offset:length}`, and special variables.
+/// Command and process substitutions, the assignments of arithmetic
+/// expansion and error reports go through `exec`.
 pub fn expand_parameters(
     input: &str,
     vars: &BTreeMap<String, String>,
     special: &SpecialVars,
+    exec: &mut dyn Executor,
+    ctx: Context,
 ) -> String {
-    let chars: alloc::vec::Vec<char> = input.chars().collect();
+    let chars: Vec<char> = input.chars().collect();
     let len = chars.len();
     let mut result = String::with_capacity(len);
     let mut i = 0;
-    let mut in_single_quote = false;
     let mut in_double_quote = false;
+    let heredoc = ctx == Context::HereDoc;
 
     while i < len {
         let ch = chars[i];
 
-        // Single-quote toggle (not inside double quotes)
-        if ch == '\'' && !in_double_quote {
-            in_single_quote = !in_single_quote;
-            i += 1;
-            continue;
-        }
-
-        // Inside single quotes: literal pass-through
-        if in_single_quote {
-            result.push(ch);
-            i += 1;
-            continue;
-        }
+        if !heredoc {
+            // Single quotes: everything up to the closing quote is literal.
+            // The empty `''` keeps an empty quoted word as a field.
+            if ch == '\'' && !in_double_quote {
+                let start = i + 1;
+                i = start;
+                while i < len && chars[i] != '\'' {
+                    i += 1;
+                }
+                result.push_str("''");
+                for &c in &chars[start..i] {
+                    protect_char(&mut result, c);
+                }
+                i += 1;
+                continue;
+            }
 
-        // Double-quote toggle
-        if ch == '"' && !in_single_quote {
-            in_double_quote = !in_double_quote;
-            i += 1;
-            continue;
+            // Double-quote toggle
+            if ch == '"' {
+                if !in_double_quote {
+                    result.push_str("''");
+                }
+                in_double_quote = !in_double_quote;
+                i += 1;
+                continue;
+            }
         }
 
         // Backslash escape
         if ch == '\\' && i + 1 < len {
-            if in_double_quote {
-                let next = chars[i + 1];
-                if matches!(next, '$' | '`' | '"' | '\\') {
+            let next = chars[i + 1];
+            if heredoc {
+                if next == '\n' {
+                    i += 2;
+                    continue;
+                }
+                if matches!(next, '$' | '`' | '\\') {
                     result.push(next);
                     i += 2;
                     continue;
                 }
+            } else if in_double_quote {
+                if matches!(next, '$' | '`' | '"' | '\\') {
+                    protect_char(&mut result, next);
+                    i += 2;
+                    continue;
+                }
             } else {
-                result.push(chars[i + 1]);
+                // Left for quote removal
+                result.push('\\');
+                result.push(next);
                 i += 2;
                 continue;
             }
         }
 
+        // `cmd` command substitution
+        if ch == '`' {
+            let start = i + 1;
+            i = start;
+            while i < len && chars[i] != '`' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            let cmd = unescape_backquoted(&chars[start..i.min(len)]);
+            i += 1;
+            let output = exec.command(&cmd);
+            push_expansion(&mut result, &output, in_double_quote, ctx);
+            continue;
+        }
+
+        // <(cmd) and >(cmd) process substitution
+        if !heredoc
+            && !in_double_quote
+            && (ch == '<' || ch == '>')
+            && i + 1 < len
+            && chars[i + 1] == '('
+        {
+            if let Some(close) = find_closing_paren(&chars[i + 2..]) {
+                let cmd: String = chars[i + 2..i + 2 + close].iter().collect();
+                let path = exec.process(&cmd, ch == '>');
+                push_expansion(&mut result, &path, false, ctx);
+                i += close + 3;
+                continue;
+            }
+        }
+
         // Dollar expansion
         if ch == '$' && i + 1 < len {
             let next = chars[i + 1];
@@ -72,8 +143,21 @@ pub fn expand_parameters(
             // ${...} braced expansion
             if next == '{' {
                 i += 2;
+                // "${ARR[@]}" expands to one field per element
+                if in_double_quote {
+                    if let Some(close) = find_closing_brace(&chars[i..]) {
+                        let inner: String = chars[i..i + close].iter().collect();
+                        if let Some(name) = inner.strip_suffix("[@]") {
+                            if name.chars().all(is_var_char) && !name.is_empty() {
+                                push_fields(&mut result, &array_elements(name, vars));
+                                i += close + 1;
+                                continue;
+                            }
+                        }
+                    }
+                }
                 let (expanded, consumed) = expand_braced_param(&chars[i..], vars, special);
-                result.push_str(&expanded);
+                push_expansion(&mut result, &expanded, in_double_quote, ctx);
                 i += consumed;
                 continue;
             }
@@ -106,71 +190,60 @@ pub fn expand_parameters(
                 if i + 1 < len {
                     i += 2;
                 }
-                let val = crate::parser::arithmetic::eval_arithmetic(&expr, vars).unwrap_or(0);
-                result.push_str(&format!("{}", val));
+                let mut assign = |name: &str, value: i64| exec.assign(name, &format!("{}", value));
+                let val = match crate::parser::arithmetic::eval_arithmetic(&expr, vars, &mut assign)
+                {
+                    Ok(val) => val,
+                    Err(e) => {
+                        exec.error(&format!("{}: {}", expr.trim(), e));
+                        0
+                    }
+                };
+                push_expansion(&mut result, &format!("{}", val), in_double_quote, ctx);
                 continue;
             }
 
-            // $(cmd) command substitution placeholder
+            // $(cmd) command substitution; an unterminated `$(` is literal
             if next == '(' {
-                // For now, emit the raw $(...) -- command substitution is
-                // handled during execution, not during expansion.
-                result.push('$');
-                i += 1;
-                continue;
+                let start = i + 2;
+                if let Some(close) = find_closing_paren(&chars[start..]) {
+                    let cmd: String = chars[start..start + close].iter().collect();
+                    i = start + close + 1;
+                    let output = exec.command(&cmd);
+                    push_expansion(&mut result, &output, in_double_quote, ctx);
+                    continue;
+                }
             }
 
             // Special variables
-            match next {
-                '?' => {
-                    result.push_str(&format!("{}", special.exit_status));
-                    i += 2;
-                    continue;
-                }
-                '$' => {
-                    result.push_str(&format!("{}", special.pid));
-                    i += 2;
-                    continue;
-                }
-                '!' => {
-                    result.push_str(&format!("{}", special.last_bg_pid));
-                    i += 2;
-                    continue;
-                }
-                '#' => {
-                    result.push_str(&format!("{}", special.argc));
-                    i += 2;
-                    continue;
-                }
-                '0' => {
-                    result.push_str(&special.arg0);
+            let value = match next {
+                '?' => Some(format!("{}", special.exit_status)),
+                '$' => Some(format!("{}", special.pid)),
+                '!' => Some(format!("{}", special.last_bg_pid)),
+                '#' => Some(format!("{}", special.argc)),
+                '0' => Some(special.arg0.clone()),
+                '-' => Some(special.flags.clone()),
+                '_' => Some(special.last_arg.clone()),
+                '@' if in_double_quote => {
+                    // "$@" expands to one field per positional parameter
+                    push_fields(&mut result, &special.positional);
                     i += 2;
                     continue;
                 }
-                '-' => {
-                    result.push_str(&special.flags);
-                    i += 2;
-                    continue;
-                }
-                '_' => {
-                    result.push_str(&special.last_arg);
-                    i += 2;
-                    continue;
-                }
-                '@' | '*' => {
-                    let sep = " ";
-                    result.push_str(&special.positional.join(sep));
-                    i += 2;
-                    continue;
-                }
-                _ => {}
+                '@' | '*' => Some(special.positional.join(" ")),
+                _ => None,
+            };
+            if let Some(value) = value {
+                push_expansion(&mut result, &value, in_double_quote, ctx);
+                i += 2;
+                continue;
             }
 
             // Positional parameters: $1 - $9
             if next.is_ascii_digit() && next != '0' {
                 let idx = (next as u8 - b'1') as usize;
                 if idx < special.positional.len() {
-                    result.push_str(&special.positional[idx]);
+                    push_expansion(&mut result, &special.positional[idx], in_double_quote, ctx);
                 }
                 i += 2;
                 continue;
@@ -185,7 +258,7 @@ pub fn expand_parameters(
                 }
                 let name: String = chars[start..i].iter().collect();
                 if let Some(val) = vars.get(&name) {
-                    result.push_str(val);
+                    push_expansion(&mut result, val, in_double_quote, ctx);
                 }
                 continue;
             }
@@ -199,7 +272,7 @@ pub fn expand_parameters(
         // Tilde expansion (only at word start or after : in assignments)
         if ch == '~'
             && i == 0
-            && !in_double_quote
+            && !heredoc
             && (i + 1 >= len || chars[i + 1] == '/' || chars[i + 1] == ' ')
         {
             if let Some(home) = vars.get("HOME") {
@@ -209,13 +282,107 @@ pub fn expand_parameters(
             }
         }
 
-        result.push(ch);
+        if in_double_quote {
+            protect_char(&mut result, ch);
+        } else {
+            result.push(ch);
+        }
         i += 1;
     }
 
     result
 }
 
+/// Append the result of an expansion.  In a command word, a quoted result
+/// is protected from field splitting and an unquoted one only has its quote
+/// characters protected, so that quote removal keeps them.
+fn push_expansion(out: &mut String, value: &str, quoted: bool, ctx: Context) {
+    if ctx == Context::HereDoc {
+        out.push_str(value);
+        return;
+    }
+    for c in value.chars() {
+        if quoted || matches!(c, '\\' | '\'' | '"') {
+            protect_char(out, c);
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+/// Append quoted `fields` (`"$@"`, `"${ARR[@]}"`) as separate words.
+fn push_fields(out: &mut String, fields: &[String]) {
+    for (n, field) in fields.iter().enumerate() {
+        if n > 0 {
+            out.push_str(" ''");
+        }
+        for c in field.chars() {
+            protect_char(out, c);
+        }
+    }
+}
+
+/// Append `c` so that neither field splitting nor globbing nor quote
+/// removal treats it specially.
+fn protect_char(out: &mut String, c: char) {
+    if !c.is_alphanumeric() {
+        out.push('\\');
+    }
+    out.push(c);
+}
+
+/// Undo the backslash escapes of a `` `cmd` `` body: inside backquotes a
+/// backslash only escapes `$`, `` ` `` and `\`.
+fn unescape_backquoted(chars: &[char]) -> String {
+    let mut cmd = String::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() && matches!(chars[i + 1], '$' | '`' | '\\') {
+            i += 1;
+        }
+        cmd.push(chars[i]);
+        i += 1;
+    }
+    cmd
+}
+
+/// Find the `)` closing a `$(` or `<(` whose body starts at `chars[0]`,
+/// skipping quoted text, escapes and nested parentheses.
+fn find_closing_paren(chars: &[char]) -> Option<usize> {
+    let mut depth = 1u32;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '\'' {
+                    i += 1;
+                }
+            }
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
 /// Special shell variables.
 #[derive(Debug, Clone)]
 pub struct SpecialVars {
@@ -234,7 +401,7 @@ pub struct SpecialVars {
     /// `$_` -- last argument of previous command.
     pub last_arg: String,
     /// Positional parameters `$1`, `$2`, ...
-    pub positional: alloc::vec::Vec<String>,
+    pub positional: Vec<String>,
 }
 
 impl Default for SpecialVars {
@@ -247,7 +414,7 @@ impl Default for SpecialVars {
             arg0: String::from("vsh"),
             flags: String::new(),
             last_arg: String::new(),
-            positional: alloc::vec::Vec::new(),
+            positional: Vec::new(),
         }
     }
 }
@@ -275,8 +442,14 @@ fn expand_braced_param(
     let inner: String = chars[..close].iter().collect();
     let consumed = close + 1;
 
-    // ${#VAR} -- string length
+    // ${#VAR} -- string length; ${#ARR[@]} -- element count
     if let Some(var_name) = inner.strip_prefix('#') {
+        if let Some(array) = var_name
+            .strip_suffix("[@]")
+            .or_else(|| var_name.strip_suffix("[*]"))
+        {
+            return (format!("{}", array_len(array, vars)), consumed);
+        }
         let length = vars.get(var_name).map(|v| v.len()).unwrap_or(0);
         return (format!("{}", length), consumed);
     }
@@ -414,6 +587,21 @@ fn expand_braced_param(
     (value, consumed)
 }
 
+/// Number of elements of indexed array `name` (flattened as `name[i]`).
+fn array_len(name: &str, vars: &BTreeMap<String, String>) -> usize {
+    vars.keys()
+        .filter_map(|key| key.strip_prefix(name)?.strip_prefix('[')?.strip_suffix(']'))
+        .filter(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        .count()
+}
+
+/// Elements of indexed array `name`, in index order.
+fn array_elements(name: &str, vars: &BTreeMap<String, String>) -> Vec<String> {
+    (0..array_len(name, vars))
+        .filter_map(|i| vars.get(&format!("{}[{}]", name, i)).cloned())
+        .collect()
+}
+
 fn get_var(name: &str, vars: &BTreeMap<String, String>, special: &SpecialVars) -> String {
     match name {
         "?" => format!("{}", special.exit_status),
@@ -470,10 +658,6 @@ fn is_var_char(ch: char) -> bool {
 // Pattern matching helpers
 // ---------------------------------------------------------------------------
 
-fn pattern_match(pattern: &str, text: &str) -> bool {
-    glob::glob_match(pattern, text)
-}
-
 fn remove_suffix_shortest(value: &str, pattern: &str) -> String {
     if pattern.is_empty() {
         return String::from(value);
@@ -0,0 +1,359 @@
+//! POSIX extended regular expressions for `[[ string =~ regex ]]`.
+//!
+//! Supports literals and `\`-escaped characters, `.`, bracket expressions
+//! (`[abc]`, `[^a-z]`, `[[:alpha:]]`), the anchors `^` and `$`, groups with
+//! alternation (`(a|b)`, `x|y`), and the repetitions `*`, `+`, `?`, `{m}`,
+//! `{m,}` and `{m,n}`.  Matching is a backtracking search for the leftmost
+//! match, greedy at every repetition.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+/// The most a `{m,n}` bound may be, to keep matching time bounded.
+const MAX_REPEAT: u32 = 255;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    /// Alternatives and capture group number (0 for the whole regex).
+    Group(Vec<Vec<Node>>, usize),
+    Repeat(Box<Node>, u32, Option<u32>),
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Named(String),
+}
+
+/// A compiled regular expression.
+#[derive(Debug)]
+pub struct Regex {
+    root: Node,
+    groups: usize,
+}
+
+impl Regex {
+    /// Compile `pattern`, or return `None` if it is not a valid ERE.
+    pub fn new(pattern: &str) -> Option<Self> {
+        let mut parser = RegexParser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 0,
+        };
+        let alternatives = parser.parse_alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return None;
+        }
+        Some(Self {
+            root: Node::Group(alternatives, 0),
+            groups: parser.groups + 1,
+        })
+    }
+
+    /// Find the leftmost match in `text`.  Returns the matched text followed
+    /// by the text of each capture group (empty for a group that did not
+    /// take part in the match).
+    pub fn captures(&self, text: &str) -> Option<Vec<String>> {
+        let chars: Vec<char> = text.chars().collect();
+        let matcher = Matcher { text: &chars };
+        for start in 0..=chars.len() {
+            let mut caps = alloc::vec![None; self.groups];
+            if matcher.match_seq(
+                core::slice::from_ref(&self.root),
+                start,
+                &mut caps,
+                &mut |_, _| true,
+            ) {
+                return Some(
+                    caps.iter()
+                        .map(|cap| match cap {
+                            Some((s, e)) => chars[*s..*e].iter().collect(),
+                            None => String::new(),
+                        })
+                        .collect(),
+                );
+            }
+        }
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+struct RegexParser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+}
+
+impl RegexParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // alternatives: sequence ('|' sequence)*
+    fn parse_alternatives(&mut self) -> Option<Vec<Vec<Node>>> {
+        let mut alternatives = alloc::vec![self.parse_sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+        Some(alternatives)
+    }
+
+    // sequence: (atom repetition*)*
+    fn parse_sequence(&mut self) -> Option<Vec<Node>> {
+        let mut seq = Vec::new();
+        while let Some(ch) = self.peek() {
+            if ch == '|' || ch == ')' {
+                break;
+            }
+            let mut node = self.parse_atom()?;
+            while let Some((min, max)) = self.parse_repetition()? {
+                if matches!(node, Node::Start | Node::End) {
+                    return None;
+                }
+                node = Node::Repeat(Box::new(node), min, max);
+            }
+            seq.push(node);
+        }
+        Some(seq)
+    }
+
+    fn parse_atom(&mut self) -> Option<Node> {
+        let ch = self.peek()?;
+        self.pos += 1;
+        match ch {
+            '.' => Some(Node::Any),
+            '^' => Some(Node::Start),
+            '$' => Some(Node::End),
+            '[' => self.parse_class(),
+            '(' => {
+                self.groups += 1;
+                let index = self.groups;
+                let alternatives = self.parse_alternatives()?;
+                if self.peek() != Some(')') {
+                    return None;
+                }
+                self.pos += 1;
+                Some(Node::Group(alternatives, index))
+            }
+            '\\' => {
+                let escaped = self.peek()?;
+                self.pos += 1;
+                Some(Node::Char(escaped))
+            }
+            '*' | '+' | '?' => None,
+            _ => Some(Node::Char(ch)),
+        }
+    }
+
+    /// `*`, `+`, `?` or a `{m,n}` bound after an atom.  A `{` that does not
+    /// start a valid bound is an error.
+    fn parse_repetition(&mut self) -> Option<Option<(u32, Option<u32>)>> {
+        let rep = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self.parse_count()?;
+                let max = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    if self.peek() == Some('}') {
+                        None
+                    } else {
+                        Some(self.parse_count()?)
+                    }
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') || max.is_some_and(|max| max < min) {
+                    return None;
+                }
+                (min, max)
+            }
+            _ => return Some(None),
+        };
+        self.pos += 1;
+        Some(Some(rep))
+    }
+
+    fn parse_count(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let mut n: u32 = 0;
+        while let Some(d) = self.peek().and_then(|c| c.to_digit(10)) {
+            n = n * 10 + d;
+            if n > MAX_REPEAT {
+                return None;
+            }
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        Some(n)
+    }
+
+    /// Bracket expression, after the `[`.
+    fn parse_class(&mut self) -> Option<Node> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let ch = self.peek()?;
+            self.pos += 1;
+            if ch == ']' && !first {
+                break;
+            }
+            first = false;
+            if ch == '[' && self.peek() == Some(':') {
+                let rest: String = self.chars[self.pos + 1..].iter().collect();
+                let end = rest.find(":]")?;
+                let name = String::from(&rest[..end]);
+                self.pos += 1 + name.chars().count() + 2;
+                items.push(ClassItem::Named(name));
+                continue;
+            }
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                let hi = self.chars[self.pos + 1];
+                self.pos += 2;
+                items.push(ClassItem::Range(ch, hi));
+            } else {
+                items.push(ClassItem::Char(ch));
+            }
+        }
+        Some(Node::Class(items, negated))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Matcher
+// ---------------------------------------------------------------------------
+
+type Captures = Vec<Option<(usize, usize)>>;
+
+struct Matcher<'a> {
+    text: &'a [char],
+}
+
+impl Matcher<'_> {
+    /// Match `seq` at `pos`, then call `cont` with the end position; true
+    /// if some way of matching lets `cont` succeed.
+    fn match_seq(
+        &self,
+        seq: &[Node],
+        pos: usize,
+        caps: &mut Captures,
+        cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        let (node, rest) = match seq.split_first() {
+            Some(split) => split,
+            None => return cont(pos, caps),
+        };
+        match node {
+            Node::Start => pos == 0 && self.match_seq(rest, pos, caps, cont),
+            Node::End => pos == self.text.len() && self.match_seq(rest, pos, caps, cont),
+            Node::Group(alternatives, index) => {
+                for alt in alternatives {
+                    let saved = caps.clone();
+                    let matched = self.match_seq(alt, pos, caps, &mut |end, caps| {
+                        let old = caps[*index];
+                        caps[*index] = Some((pos, end));
+                        if self.match_seq(rest, end, caps, cont) {
+                            return true;
+                        }
+                        caps[*index] = old;
+                        false
+                    });
+                    if matched {
+                        return true;
+                    }
+                    *caps = saved;
+                }
+                false
+            }
+            Node::Repeat(inner, min, max) => {
+                self.match_repeat(inner, *min, *max, 0, pos, rest, caps, cont)
+            }
+            _ => match self.text.get(pos) {
+                Some(&ch) if self.match_char(node, ch) => self.match_seq(rest, pos + 1, caps, cont),
+                _ => false,
+            },
+        }
+    }
+
+    /// Match `inner` repeated between `min` and `max` times (`count` so
+    /// far), as many times as possible, followed by `rest`.
+    #[allow(clippy::too_many_arguments)]
+    fn match_repeat(
+        &self,
+        inner: &Node,
+        min: u32,
+        max: Option<u32>,
+        count: u32,
+        pos: usize,
+        rest: &[Node],
+        caps: &mut Captures,
+        cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        if max.is_none_or(|max| count < max) {
+            let more = self.match_seq(core::slice::from_ref(inner), pos, caps, &mut |end, caps| {
+                // An empty iteration cannot lead anywhere new
+                if end == pos && count >= min {
+                    return false;
+                }
+                self.match_repeat(inner, min, max, count + 1, end, rest, caps, cont)
+            });
+            if more {
+                return true;
+            }
+        }
+        count >= min && self.match_seq(rest, pos, caps, cont)
+    }
+
+    fn match_char(&self, node: &Node, ch: char) -> bool {
+        match node {
+            Node::Char(c) => *c == ch,
+            Node::Any => true,
+            Node::Class(items, negated) => {
+                let matched = items.iter().any(|item| match item {
+                    ClassItem::Char(c) => *c == ch,
+                    ClassItem::Range(lo, hi) => *lo <= ch && ch <= *hi,
+                    ClassItem::Named(name) => match_named_class(name, ch),
+                });
+                matched != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Test `ch` against a `[:name:]` character class.
+fn match_named_class(name: &str, ch: char) -> bool {
+    match name {
+        "alpha" => ch.is_ascii_alphabetic(),
+        "digit" => ch.is_ascii_digit(),
+        "alnum" => ch.is_ascii_alphanumeric(),
+        "upper" => ch.is_ascii_uppercase(),
+        "lower" => ch.is_ascii_lowercase(),
+        "space" => ch.is_ascii_whitespace() || ch == '\x0b',
+        "blank" => ch == ' ' || ch == '\t',
+        "punct" => ch.is_ascii_punctuation(),
+        "xdigit" => ch.is_ascii_hexdigit(),
+        "cntrl" => ch.is_ascii_control(),
+        "print" => ch.is_ascii_graphic() || ch == ' ',
+        "graph" => ch.is_ascii_graphic(),
+        _ => false,
+    }
+}
@@ -0,0 +1,103 @@
+//! Here-document handling.
+//!
+//! Supports `<<DELIM ... DELIM`, `<<-DELIM ... DELIM` (tab stripping),
+//! and `<<<'here string'` (here-strings).
+
+use alloc::string::String;
+
+/// A pending here-document that needs its body collected.
+#[derive(Debug, Clone)]
+pub struct PendingHereDoc {
+    /// The delimiter word (without quotes).
+    pub delimiter: String,
+    /// Whether to strip leading tabs (`<<-`).
+    pub strip_tabs: bool,
+    /// Whether the delimiter was quoted (suppresses expansion in the body).
+    pub quoted: bool,
+}
+
+impl PendingHereDoc {
+    pub fn new(raw_delimiter: &str, strip_tabs: bool) -> Self {
+        let (delimiter, quoted) = strip_heredoc_quotes(raw_delimiter);
+        Self {
+            delimiter,
+            strip_tabs,
+            quoted,
+        }
+    }
+}
+
+/// Strip quotes from a here-document delimiter and determine if it was
+/// quoted.
+///
+/// Bash rules:
+/// - `<<'EOF'`, `<<"EOF"`, `<<\EOF` or any other quoting anywhere in the word
+///   (`<<E"O"F`): the delimiter is the word after quote removal (`EOF`), and
+///   the body is NOT subject to expansion.
+/// - `<<EOF`: the delimiter is `EOF`, and the body IS subject to expansion.
+fn strip_heredoc_quotes(raw: &str) -> (String, bool) {
+    let mut result = String::with_capacity(raw.len());
+    let mut quoted = false;
+    let mut quote: Option<char> = None;
+    let mut chars = raw.chars();
+
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (None, '\'') | (None, '"') => {
+                quote = Some(ch);
+                quoted = true;
+            }
+            (Some(q), _) if ch == q => quote = None,
+            (None, '\\') | (Some('"'), '\\') => {
+                quoted = true;
+                if let Some(next) = chars.next() {
+                    result.push(next);
+                }
+            }
+            _ => result.push(ch),
+        }
+    }
+
+    (result, quoted)
+}
+
+/// Read one line of a here-document body and decide whether it ends the
+/// document.
+///
+/// `line` excludes the newline.  With `<<-` leading tabs are removed from
+/// body lines and from the delimiter line.  Returns `true` at the
+/// delimiter; otherwise the line is appended to `body` with its newline.
+pub fn take_heredoc_line(doc: &PendingHereDoc, line: &str, body: &mut String) -> bool {
+    let line = if doc.strip_tabs {
+        line.trim_start_matches('\t')
+    } else {
+        line
+    };
+    if line == doc.delimiter {
+        return true;
+    }
+    body.push_str(line);
+    body.push('\n');
+    false
+}
+
+/// Parse a here-string value from `<<<word`.
+///
+/// The word may be quoted (single or double) or unquoted.
+#[allow(dead_code)] // Used when here-strings are expanded
+pub fn parse_here_string(word: &str) -> String {
+    let bytes = word.as_bytes();
+    if bytes.is_empty() {
+        return String::new();
+    }
+
+    // Strip surrounding quotes if present
+    if bytes.len() >= 2
+        && ((bytes[0] == b'\'' && bytes[bytes.len() - 1] == b'\'')
+            || (bytes[0] == b'"' && bytes[bytes.len() - 1] == b'"'))
+    {
+        return String::from(&word[1..word.len() - 1]);
+    }
+
+    String::from(word)
+}
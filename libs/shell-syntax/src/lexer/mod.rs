@@ -1,7 +1,12 @@
-//! Lexer for the vsh shell.
+//! Shell lexer.
 //!
 //! Transforms a raw input string into a sequence of [`Token`]s.  Handles
 //! all bash operators, quoting, here-documents, and here-strings.
+//!
+//! `$(...)`, `` `...` ``, `<(...)` and `>(...)` are kept whole inside the
+//! word they appear in, however they nest; the expander runs them.  A
+//! here-document body is read from the lines following the operator's line
+//! and emitted as a [`TokenKind::HereDocBody`] right after the operator.
 
 pub mod heredoc;
 pub mod quote;
@@ -25,6 +30,14 @@ pub struct Lexer {
     col: u32,
     /// Pending here-documents to collect.
     pub pending_heredocs: Vec<PendingHereDoc>,
+    /// Collected here-document bodies (body, delimiter quoted), in order.
+    heredoc_bodies: Vec<(String, bool)>,
+    /// Input ended before the delimiter of a here-document.
+    pub unterminated_heredoc: bool,
+    /// Inside `[[ ... ]]`.
+    in_conditional: bool,
+    /// The next word is the regular expression after `=~`.
+    regex_next: bool,
     /// Quote tracking state.
     #[allow(dead_code)] // Will be used for multi-line input tracking
     quote_state: QuoteState,
@@ -39,6 +52,10 @@ impl Lexer {
             line: 1,
             col: 1,
             pending_heredocs: Vec::new(),
+            heredoc_bodies: Vec::new(),
+            unterminated_heredoc: false,
+            in_conditional: false,
+            regex_next: false,
             quote_state: QuoteState::new(),
         }
     }
@@ -54,7 +71,20 @@ impl Lexer {
                 break;
             }
         }
-        tokens
+
+        // Attach each collected body to its operator
+        let mut bodies = core::mem::take(&mut self.heredoc_bodies).into_iter();
+        let mut result = Vec::with_capacity(tokens.len());
+        for tok in tokens {
+            let span = tok.span;
+            let is_heredoc = matches!(tok.kind, TokenKind::DLess | TokenKind::DLessDash);
+            result.push(tok);
+            if is_heredoc {
+                let (body, quoted) = bodies.next().unwrap_or_default();
+                result.push(Token::new(TokenKind::HereDocBody(body, quoted), span));
+            }
+        }
+        result
     }
 
     /// Current position as a span.
@@ -116,15 +146,23 @@ impl Lexer {
         let span = self.span();
 
         match self.peek() {
-            None => Token::new(TokenKind::Eof, span),
+            None => {
+                self.collect_heredocs();
+                Token::new(TokenKind::Eof, span)
+            }
             Some('#') => {
                 self.skip_comment();
                 self.next_token()
             }
             Some('\n') => {
                 self.advance();
+                self.collect_heredocs();
                 Token::new(TokenKind::Newline, span)
             }
+            Some(_) if self.regex_next => {
+                self.regex_next = false;
+                self.read_regex_word(span)
+            }
             Some(_ch) => {
                 // Check for operators first
                 if let Some(tok) = self.try_operator(span) {
@@ -136,6 +174,39 @@ impl Lexer {
         }
     }
 
+    /// Read the bodies of the pending here-documents from the lines that
+    /// follow.  At end of input a missing body is empty and a missing
+    /// delimiter sets `unterminated_heredoc`.
+    fn collect_heredocs(&mut self) {
+        for doc in core::mem::take(&mut self.pending_heredocs) {
+            let mut body = String::new();
+            let mut terminated = false;
+            while self.peek().is_some() {
+                let line = self.read_line();
+                if heredoc::take_heredoc_line(&doc, &line, &mut body) {
+                    terminated = true;
+                    break;
+                }
+            }
+            if !terminated {
+                self.unterminated_heredoc = true;
+            }
+            self.heredoc_bodies.push((body, doc.quoted));
+        }
+    }
+
+    /// Read the rest of the current line, consuming its newline.
+    fn read_line(&mut self) -> String {
+        let mut line = String::new();
+        while let Some(ch) = self.advance() {
+            if ch == '\n' {
+                break;
+            }
+            line.push(ch);
+        }
+        line
+    }
+
     /// Try to read an operator token. Returns `None` if the current position
     /// does not start an operator.
     fn try_operator(&mut self, span: Span) -> Option<Token> {
@@ -143,6 +214,11 @@ impl Lexer {
         let c1 = self.peek_at(1);
         let c2 = self.peek_at(2);
 
+        // `<(` and `>(` start a process substitution word
+        if (c0 == '<' || c0 == '>') && c1 == Some('(') {
+            return None;
+        }
+
         let (kind, consume) = match (c0, c1, c2) {
             // Three-character operators
             ('<', Some('<'), Some('<')) => (TokenKind::TLess, 3),
@@ -160,11 +236,9 @@ impl Lexer {
             ('<', Some('<'), _) => (TokenKind::DLess, 2),
             ('<', Some('>'), _) => (TokenKind::LessGreater, 2),
             ('<', Some('&'), _) => (TokenKind::LessAnd, 2),
-            ('<', Some('('), _) => (TokenKind::ProcSubIn, 2),
             ('>', Some('>'), _) => (TokenKind::DGreater, 2),
             ('>', Some('&'), _) => (TokenKind::GreaterAnd, 2),
             ('>', Some('|'), _) => (TokenKind::Clobber, 2),
-            ('>', Some('('), _) => (TokenKind::ProcSubOut, 2),
 
             // Single-character operators
             ('|', _, _) => (TokenKind::Pipe, 1),
@@ -204,6 +278,15 @@ impl Lexer {
         while let Some(ch) = self.peek() {
             // If unquoted, check for metacharacters that end the word
             if !quote_state.is_quoted() {
+                // Process substitution: read up to the matching `)`
+                if (ch == '<' || ch == '>') && self.peek_at(1) == Some('(') {
+                    word.push(ch);
+                    word.push('(');
+                    self.advance();
+                    self.advance();
+                    quote_state.enter_command_sub();
+                    continue;
+                }
                 if is_metachar(ch) {
                     break;
                 }
@@ -288,8 +371,16 @@ impl Lexer {
 
         // Check if this is a reserved word (only in command position)
         if let Some(reserved) = TokenKind::reserved_word(&word) {
+            match reserved {
+                TokenKind::DLBracket => self.in_conditional = true,
+                TokenKind::DRBracket => self.in_conditional = false,
+                _ => {}
+            }
             return Token::new(reserved, span);
         }
+        if self.in_conditional && word == "=~" {
+            self.regex_next = true;
+        }
 
         // Check for `{` and `}` as words
         if word == "{" {
@@ -302,25 +393,90 @@ impl Lexer {
         Token::new(TokenKind::Word(word), span)
     }
 
-    /// Read a raw word (for here-document delimiter, etc.) without quote
-    /// processing.
-    fn read_raw_word(&mut self) -> String {
+    /// Read the regular expression after `=~` in `[[ ]]`.  `(`, `)`, `|`,
+    /// `<` and `>` are ordinary characters in it, and blanks inside
+    /// parentheses do not end it.
+    fn read_regex_word(&mut self, span: Span) -> Token {
         let mut word = String::new();
-        loop {
-            match self.peek() {
-                Some(ch) if ch == ' ' || ch == '\t' || ch == '\n' => break,
-                Some(ch) if is_metachar(ch) && ch != '\'' && ch != '"' => break,
-                Some(ch) => {
+        let mut depth = 0usize;
+
+        while let Some(ch) = self.peek() {
+            match ch {
+                ' ' | '\t' | '\n' if depth == 0 => break,
+                '\\' => {
+                    word.push(ch);
+                    self.advance();
+                    if let Some(escaped) = self.advance() {
+                        word.push(escaped);
+                    }
+                }
+                '\'' | '"' => {
+                    word.push(ch);
+                    self.advance();
+                    while let Some(c) = self.advance() {
+                        word.push(c);
+                        if c == ch {
+                            break;
+                        }
+                        if c == '\\' && ch == '"' {
+                            if let Some(escaped) = self.advance() {
+                                word.push(escaped);
+                            }
+                        }
+                    }
+                }
+                '(' => {
+                    depth += 1;
+                    word.push(ch);
+                    self.advance();
+                }
+                ')' => {
+                    depth = depth.saturating_sub(1);
+                    word.push(ch);
+                    self.advance();
+                }
+                _ => {
                     word.push(ch);
                     self.advance();
                 }
-                None => break,
+            }
+        }
+
+        Token::new(TokenKind::Word(word), span)
+    }
+
+    /// Read a raw word (for here-document delimiter, etc.), keeping its
+    /// quotes and escapes.  Quoted metacharacters do not end it.
+    fn read_raw_word(&mut self) -> String {
+        let mut word = String::new();
+        let mut quote: Option<char> = None;
+        while let Some(ch) = self.peek() {
+            match quote {
+                None if is_metachar(ch) => break,
+                None if ch == '\'' || ch == '"' => quote = Some(ch),
+                Some(q) if ch == q => quote = None,
+                _ => {}
+            }
+            word.push(ch);
+            self.advance();
+            if ch == '\\' && quote != Some('\'') {
+                if let Some(next) = self.advance() {
+                    word.push(next);
+                }
             }
         }
         word
     }
 }
 
+/// Whether `input` ends inside a here-document, so an interactive shell
+/// has to read more lines before running it.
+pub fn heredoc_incomplete(input: &str) -> bool {
+    let mut lexer = Lexer::new(input);
+    lexer.tokenize();
+    lexer.unterminated_heredoc
+}
+
 /// Returns true if `ch` is a shell metacharacter.
 fn is_metachar(ch: char) -> bool {
     matches!(
@@ -32,15 +32,24 @@ pub struct QuoteState {
     stack: Vec<QuoteContext>,
     /// Paren depth inside command substitution (for matching nested parens).
     paren_depth: u32,
+    /// Paren depths of the enclosing command substitutions.
+    outer_paren_depths: Vec<u32>,
     /// Brace depth inside parameter expansion.
     brace_depth: u32,
 }
 
+impl Default for QuoteState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl QuoteState {
     pub fn new() -> Self {
         Self {
             stack: Vec::new(),
             paren_depth: 0,
+            outer_paren_depths: Vec::new(),
             brace_depth: 0,
         }
     }
@@ -78,7 +87,18 @@ impl QuoteState {
 
     /// Pop the current quoting context.
     pub fn pop(&mut self) -> Option<QuoteContext> {
-        self.stack.pop()
+        let ctx = self.stack.pop();
+        if ctx == Some(QuoteContext::CommandSub) {
+            self.paren_depth = self.outer_paren_depths.pop().unwrap_or(0);
+        }
+        ctx
+    }
+
+    /// Enter a command substitution body (after `$(`, `<(` or `>(`).
+    pub fn enter_command_sub(&mut self) {
+        self.outer_paren_depths.push(self.paren_depth);
+        self.paren_depth = 0;
+        self.push(QuoteContext::CommandSub);
     }
 
     /// Process a character and update state. Returns `true` if the character
@@ -105,10 +125,11 @@ impl QuoteState {
                 if ch == '`' {
                     self.pop();
                     QuoteAction::Consumed
-                } else if ch == '\\' {
-                    // In backtick substitution, backslash only escapes
-                    // $, `, \, and newline.
-                    QuoteAction::Literal
+                } else if ch == '\\' && peek.is_some() {
+                    // Keep the escaped character (an escaped backquote must
+                    // not end the substitution); the expander unescapes
+                    // $, `, and \.
+                    QuoteAction::EscapeNext
                 } else {
                     QuoteAction::Literal
                 }
@@ -139,6 +160,9 @@ impl QuoteState {
         peek2: Option<char>,
     ) -> QuoteAction {
         match ch {
+            // Only reached inside `$(...)` and `$((...))`: a top-level
+            // backslash is handled by the lexer
+            '\\' if peek.is_some() => QuoteAction::EscapeNext,
             '\'' => {
                 self.push(QuoteContext::Single);
                 QuoteAction::Consumed
@@ -159,8 +183,7 @@ impl QuoteState {
                             QuoteAction::StartArithSub // consume `$((`, caller
                                                        // skips 2 chars
                         } else {
-                            self.push(QuoteContext::CommandSub);
-                            self.paren_depth = 0;
+                            self.enter_command_sub();
                             QuoteAction::StartCmdSub // consume `$(`, caller
                                                      // skips 1 char
                         }
@@ -227,8 +250,7 @@ impl QuoteState {
                         self.push(QuoteContext::ArithmeticSub);
                         QuoteAction::StartArithSub
                     } else {
-                        self.push(QuoteContext::CommandSub);
-                        self.paren_depth = 0;
+                        self.enter_command_sub();
                         QuoteAction::StartCmdSub
                     }
                 }
@@ -1,4 +1,4 @@
-//! Token types for the vsh lexer.
+//! Token types for the shell lexer.
 //!
 //! Defines the complete set of tokens produced by lexing a bash command line.
 
@@ -114,12 +114,6 @@ pub enum TokenKind {
     /// `&>>` (append stdout+stderr)
     AndDGreater,
 
-    // --- Process substitution markers ---
-    /// `<(` (process substitution input)
-    ProcSubIn,
-    /// `>(` (process substitution output)
-    ProcSubOut,
-
     // --- Reserved words ---
     /// `if`
     If,
@@ -168,9 +162,10 @@ pub enum TokenKind {
     /// End of input
     Eof,
 
-    /// A here-document body. Produced after the corresponding `<<DELIM`
-    /// operator is found and the body is collected.
-    HereDocBody(String),
+    /// A here-document body and whether its delimiter was quoted (which
+    /// suppresses expansion of the body).  Placed right after the
+    /// corresponding `<<DELIM` operator once the body has been collected.
+    HereDocBody(String, bool),
 }
 
 impl TokenKind {
@@ -0,0 +1,22 @@
+//! Shell language front end shared by vsh and the kernel shell.
+//!
+//! Turns shell input into words and commands and expands them, without
+//! running anything:
+//!
+//! - [`lexer`]: tokens, quoting and here-documents
+//! - [`parser`]: the command AST, arithmetic and `[[ ]]` expressions
+//! - [`expand`]: brace, tilde, parameter and arithmetic expansion, word
+//!   splitting, glob and regex matching
+//!
+//! Whatever needs a running shell -- command and process substitution,
+//! variable assignment, error reporting -- goes through the
+//! [`expand::Executor`] the caller supplies.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod error;
+pub mod expand;
+pub mod lexer;
+pub mod parser;
@@ -2,9 +2,10 @@
 //!
 //! Implements a recursive descent parser for C-like integer arithmetic
 //! expressions including:
-//! - Integer literals (decimal, octal 0NNN, hex 0xNNN)
-//! - Variable references (bare names)
+//! - Integer literals (decimal, octal 0NNN, hex 0xNNN, base#digits)
+//! - Variable references (bare names and `name[expr]` array elements)
 //! - Unary operators: +, -, ~, !
+//! - Increment and decrement: ++id, --id, id++, id--
 //! - Binary operators: +, -, *, /, %, **, <<, >>
 //! - Comparison: <, >, <=, >=, ==, !=
 //! - Bitwise: &, ^, |
@@ -14,16 +15,25 @@
 //! - Comma operator
 //! - Parenthesized expressions
 
-use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
 
-use crate::error::{Result, VshError};
+use crate::error::{Error, Result};
 
 /// Evaluate an arithmetic expression string with the given variable context.
-pub fn eval_arithmetic(expr: &str, vars: &BTreeMap<String, String>) -> Result<i64> {
+///
+/// Each assignment the expression makes (`=`, `+=`, `++`, ...) is passed
+/// to `assign` as it happens, with the variable name (`name[i]` for an
+/// array element) and the new value; later reads in the same expression
+/// see the new value.  Operands skipped by `&&`, `||` and `?:` are not
+/// evaluated.
+pub fn eval_arithmetic(
+    expr: &str,
+    vars: &BTreeMap<String, String>,
+    assign: &mut dyn FnMut(&str, i64),
+) -> Result<i64> {
     let tokens = tokenize_arith(expr)?;
-    let mut parser = ArithParser::new(&tokens, vars);
-    let result = parser.parse_comma()?;
-    Ok(result)
+    let mut parser = ArithParser::new(&tokens, vars, assign);
+    parser.parse_all()
 }
 
 // ---------------------------------------------------------------------------
@@ -73,6 +83,8 @@ enum ArithToken {
     Comma,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
 }
 
 fn tokenize_arith(expr: &str) -> Result<Vec<ArithToken>> {
@@ -90,38 +102,16 @@ fn tokenize_arith(expr: &str) -> Result<Vec<ArithToken>> {
             continue;
         }
 
-        // Numbers
+        // Numbers: decimal, octal 0NNN, hex 0xNNN and base#digits
         if ch.is_ascii_digit() {
             let start = i;
-            // Hex
-            if ch == '0' && i + 1 < len && (chars[i + 1] == 'x' || chars[i + 1] == 'X') {
-                i += 2;
-                while i < len && chars[i].is_ascii_hexdigit() {
-                    i += 1;
-                }
-                let s: String = chars[start..i].iter().collect();
-                let n = i64::from_str_radix(&s[2..], 16).map_err(|_| VshError::NotANumber(s))?;
-                tokens.push(ArithToken::Number(n));
-            }
-            // Octal (leading 0)
-            else if ch == '0' && i + 1 < len && chars[i + 1].is_ascii_digit() {
+            while i < len
+                && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '#' | '@' | '_'))
+            {
                 i += 1;
-                while i < len && chars[i].is_ascii_digit() {
-                    i += 1;
-                }
-                let s: String = chars[start..i].iter().collect();
-                let n = i64::from_str_radix(&s[1..], 8).map_err(|_| VshError::NotANumber(s))?;
-                tokens.push(ArithToken::Number(n));
-            }
-            // Decimal
-            else {
-                while i < len && chars[i].is_ascii_digit() {
-                    i += 1;
-                }
-                let s: String = chars[start..i].iter().collect();
-                let n = parse_decimal(&s)?;
-                tokens.push(ArithToken::Number(n));
             }
+            let s: String = chars[start..i].iter().collect();
+            tokens.push(ArithToken::Number(parse_number(&s)?));
             continue;
         }
 
@@ -148,11 +138,12 @@ fn tokenize_arith(expr: &str) -> Result<Vec<ArithToken>> {
                 tokens.push(ArithToken::DoubleStar);
                 i += 2;
             }
-            ('+', Some('+')) => {
+            // After an operand, `5--2` is a subtraction of `-2`
+            ('+', Some('+')) if !after_operand(&tokens) => {
                 tokens.push(ArithToken::PlusPlus);
                 i += 2;
             }
-            ('-', Some('-')) => {
+            ('-', Some('-')) if !after_operand(&tokens) => {
                 tokens.push(ArithToken::MinusMinus);
                 i += 2;
             }
@@ -251,8 +242,10 @@ fn tokenize_arith(expr: &str) -> Result<Vec<ArithToken>> {
                     ',' => ArithToken::Comma,
                     '(' => ArithToken::LParen,
                     ')' => ArithToken::RParen,
+                    '[' => ArithToken::LBracket,
+                    ']' => ArithToken::RBracket,
                     _ => {
-                        return Err(VshError::Syntax(alloc::format!(
+                        return Err(Error::Syntax(format!(
                             "unexpected character '{}' in arithmetic",
                             ch
                         )));
@@ -267,6 +260,54 @@ fn tokenize_arith(expr: &str) -> Result<Vec<ArithToken>> {
     Ok(tokens)
 }
 
+/// Whether the last token ends an operand that `++` / `--` cannot apply to.
+fn after_operand(tokens: &[ArithToken]) -> bool {
+    matches!(
+        tokens.last(),
+        Some(ArithToken::Number(_) | ArithToken::RParen)
+    )
+}
+
+/// Parse an integer constant: `0x` hex, leading-`0` octal, `base#digits`
+/// (bases 2 to 64, digits `0-9a-zA-Z@_`) or decimal.
+fn parse_number(s: &str) -> Result<i64> {
+    let invalid = || Error::NotANumber(String::from(s));
+    if let Some((base, digits)) = s.split_once('#') {
+        let base = parse_decimal(base)?;
+        if !(2..=64).contains(&base) || digits.is_empty() {
+            return Err(invalid());
+        }
+        let mut n: i64 = 0;
+        for ch in digits.chars() {
+            let d = match ch {
+                '0'..='9' => ch as i64 - '0' as i64,
+                'a'..='z' => ch as i64 - 'a' as i64 + 10,
+                'A'..='Z' if base <= 36 => ch as i64 - 'A' as i64 + 10,
+                'A'..='Z' => ch as i64 - 'A' as i64 + 36,
+                '@' => 62,
+                '_' => 63,
+                _ => return Err(invalid()),
+            };
+            if d >= base {
+                return Err(invalid());
+            }
+            n = n.wrapping_mul(base).wrapping_add(d);
+        }
+        return Ok(n);
+    }
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        return u64::from_str_radix(hex, 16)
+            .map(|n| n as i64)
+            .map_err(|_| invalid());
+    }
+    if s.len() > 1 && s.starts_with('0') {
+        return u64::from_str_radix(&s[1..], 8)
+            .map(|n| n as i64)
+            .map_err(|_| invalid());
+    }
+    parse_decimal(s)
+}
+
 fn parse_decimal(s: &str) -> Result<i64> {
     let mut n: i64 = 0;
     let mut neg = false;
@@ -279,7 +320,7 @@ fn parse_decimal(s: &str) -> Result<i64> {
     while i < bytes.len() {
         let d = bytes[i];
         if !d.is_ascii_digit() {
-            return Err(VshError::NotANumber(String::from(s)));
+            return Err(Error::NotANumber(String::from(s)));
         }
         n = n.wrapping_mul(10).wrapping_add((d - b'0') as i64);
         i += 1;
@@ -294,18 +335,36 @@ fn parse_decimal(s: &str) -> Result<i64> {
 // Recursive descent parser
 // ---------------------------------------------------------------------------
 
+/// How deeply variables whose values are themselves expressions may nest.
+const MAX_RECURSION: u32 = 32;
+
 struct ArithParser<'a> {
     tokens: &'a [ArithToken],
     pos: usize,
     vars: &'a BTreeMap<String, String>,
+    /// Values assigned so far, which shadow `vars`.
+    assigned: BTreeMap<String, i64>,
+    assign: &'a mut dyn FnMut(&str, i64),
+    /// Non-zero while parsing an operand that short-circuiting skips:
+    /// nothing is assigned and division by zero is not an error.
+    noeval: u32,
+    depth: u32,
 }
 
 impl<'a> ArithParser<'a> {
-    fn new(tokens: &'a [ArithToken], vars: &'a BTreeMap<String, String>) -> Self {
+    fn new(
+        tokens: &'a [ArithToken],
+        vars: &'a BTreeMap<String, String>,
+        assign: &'a mut dyn FnMut(&str, i64),
+    ) -> Self {
         Self {
             tokens,
             pos: 0,
             vars,
+            assigned: BTreeMap::new(),
+            assign,
+            noeval: 0,
+            depth: 0,
         }
     }
 
@@ -322,17 +381,62 @@ impl<'a> ArithParser<'a> {
     fn expect(&mut self, expected: &ArithToken) -> Result<()> {
         match self.advance() {
             Some(tok) if tok == expected => Ok(()),
-            _ => Err(VshError::Syntax(String::from(
+            _ => Err(Error::Syntax(String::from(
                 "unexpected token in arithmetic",
             ))),
         }
     }
 
-    fn var_value(&self, name: &str) -> i64 {
-        self.vars
-            .get(name)
-            .and_then(|v| parse_decimal(v).ok())
-            .unwrap_or(0)
+    /// Value of a variable.  Unset and empty variables are 0; a value that
+    /// is not a number is evaluated as an expression in turn.
+    fn var_value(&self, name: &str) -> Result<i64> {
+        if let Some(&n) = self.assigned.get(name) {
+            return Ok(n);
+        }
+        let value = match self.vars.get(name) {
+            Some(v) => v.trim(),
+            None => return Ok(0),
+        };
+        if value.is_empty() {
+            return Ok(0);
+        }
+        if let Ok(n) = parse_number(value) {
+            return Ok(n);
+        }
+        if self.depth >= MAX_RECURSION {
+            return Err(Error::Syntax(format!(
+                "{}: expression recursion level exceeded",
+                name
+            )));
+        }
+        let tokens = tokenize_arith(value)?;
+        let mut ignore = |_: &str, _: i64| {};
+        let mut inner = ArithParser::new(&tokens, self.vars, &mut ignore);
+        inner.assigned = self.assigned.clone();
+        inner.noeval = 1;
+        inner.depth = self.depth + 1;
+        inner.parse_all()
+    }
+
+    fn store(&mut self, name: &str, value: i64) {
+        if self.noeval == 0 {
+            self.assigned.insert(String::from(name), value);
+            (self.assign)(name, value);
+        }
+    }
+
+    /// Parse a whole expression, which must use up every token.
+    fn parse_all(&mut self) -> Result<i64> {
+        if self.tokens.is_empty() {
+            return Ok(0);
+        }
+        let val = self.parse_comma()?;
+        if self.pos < self.tokens.len() {
+            return Err(Error::Syntax(String::from(
+                "unexpected token in arithmetic",
+            )));
+        }
+        Ok(val)
     }
 
     // Comma: expr, expr, ...
@@ -345,31 +449,114 @@ impl<'a> ArithParser<'a> {
         Ok(val)
     }
 
-    // Assignment: lvalue = expr
+    /// Variable name, with its subscript if it is an array element
+    /// (`name` or `name[expr]`), at the current position.
+    fn parse_lvalue(&mut self) -> Result<String> {
+        let name = match self.advance() {
+            Some(ArithToken::Ident(name)) => name.clone(),
+            _ => {
+                return Err(Error::Syntax(String::from(
+                    "assignment requires a variable name",
+                )))
+            }
+        };
+        if self.peek() != Some(&ArithToken::LBracket) {
+            return Ok(name);
+        }
+        self.advance();
+        let index = self.parse_comma()?;
+        self.expect(&ArithToken::RBracket)?;
+        Ok(format!("{}[{}]", name, index))
+    }
+
+    /// Position just past the lvalue starting at the current position, if
+    /// there is one.
+    fn lvalue_end(&self) -> Option<usize> {
+        if !matches!(self.peek(), Some(ArithToken::Ident(_))) {
+            return None;
+        }
+        let mut i = self.pos + 1;
+        if self.tokens.get(i) == Some(&ArithToken::LBracket) {
+            let mut depth = 0;
+            loop {
+                match self.tokens.get(i)? {
+                    ArithToken::LBracket => depth += 1,
+                    ArithToken::RBracket => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 1;
+        }
+        Some(i)
+    }
+
+    // Assignment: lvalue = expr, lvalue op= expr (right-associative)
     fn parse_assignment(&mut self) -> Result<i64> {
-        self.parse_ternary()
+        let op = self
+            .lvalue_end()
+            .and_then(|end| self.tokens.get(end))
+            .filter(|tok| is_assign_op(tok))
+            .cloned();
+        let op = match op {
+            Some(op) => op,
+            None => return self.parse_ternary(),
+        };
+
+        let name = self.parse_lvalue()?;
+        self.advance();
+        let rhs = self.parse_assignment()?;
+        let value = if op == ArithToken::Eq {
+            rhs
+        } else {
+            let old = self.var_value(&name)?;
+            self.apply_binary(&op, old, rhs)?
+        };
+        self.store(&name, value);
+        Ok(value)
     }
 
-    // Ternary: cond ? then : else
+    // Ternary: cond ? then : else (only the chosen branch is evaluated)
     fn parse_ternary(&mut self) -> Result<i64> {
         let cond = self.parse_or()?;
-        if self.peek() == Some(&ArithToken::Question) {
-            self.advance();
-            let then_val = self.parse_assignment()?;
-            self.expect(&ArithToken::Colon)?;
-            let else_val = self.parse_assignment()?;
-            Ok(if cond != 0 { then_val } else { else_val })
-        } else {
-            Ok(cond)
+        if self.peek() != Some(&ArithToken::Question) {
+            return Ok(cond);
+        }
+        self.advance();
+        if cond == 0 {
+            self.noeval += 1;
+        }
+        let then_val = self.parse_assignment()?;
+        if cond == 0 {
+            self.noeval -= 1;
+        }
+        self.expect(&ArithToken::Colon)?;
+        if cond != 0 {
+            self.noeval += 1;
+        }
+        let else_val = self.parse_assignment()?;
+        if cond != 0 {
+            self.noeval -= 1;
         }
+        Ok(if cond != 0 { then_val } else { else_val })
     }
 
     fn parse_or(&mut self) -> Result<i64> {
         let mut val = self.parse_and()?;
         while self.peek() == Some(&ArithToken::BarBar) {
             self.advance();
-            let rhs = self.parse_and()?;
-            val = if val != 0 || rhs != 0 { 1 } else { 0 };
+            if val != 0 {
+                self.noeval += 1;
+                self.parse_and()?;
+                self.noeval -= 1;
+            } else {
+                val = (self.parse_and()? != 0) as i64;
+            }
         }
         Ok(val)
     }
@@ -378,8 +565,13 @@ impl<'a> ArithParser<'a> {
         let mut val = self.parse_bitor()?;
         while self.peek() == Some(&ArithToken::AmpAmp) {
             self.advance();
-            let rhs = self.parse_bitor()?;
-            val = if val != 0 && rhs != 0 { 1 } else { 0 };
+            if val == 0 {
+                self.noeval += 1;
+                self.parse_bitor()?;
+                self.noeval -= 1;
+            } else {
+                val = (self.parse_bitor()? != 0) as i64;
+            }
         }
         Ok(val)
     }
@@ -506,31 +698,12 @@ impl<'a> ArithParser<'a> {
 
     fn parse_multiplicative(&mut self) -> Result<i64> {
         let mut val = self.parse_exponent()?;
-        loop {
-            match self.peek() {
-                Some(&ArithToken::Star) => {
-                    self.advance();
-                    let rhs = self.parse_exponent()?;
-                    val = val.wrapping_mul(rhs);
-                }
-                Some(&ArithToken::Slash) => {
-                    self.advance();
-                    let rhs = self.parse_exponent()?;
-                    if rhs == 0 {
-                        return Err(VshError::DivisionByZero);
-                    }
-                    val = val.wrapping_div(rhs);
-                }
-                Some(&ArithToken::Percent) => {
-                    self.advance();
-                    let rhs = self.parse_exponent()?;
-                    if rhs == 0 {
-                        return Err(VshError::DivisionByZero);
-                    }
-                    val = val.wrapping_rem(rhs);
-                }
-                _ => break,
-            }
+        while let Some(op @ (ArithToken::Star | ArithToken::Slash | ArithToken::Percent)) =
+            self.peek().cloned()
+        {
+            self.advance();
+            let rhs = self.parse_exponent()?;
+            val = self.apply_binary(&op, val, rhs)?;
         }
         Ok(val)
     }
@@ -567,27 +740,99 @@ impl<'a> ArithParser<'a> {
                 let val = self.parse_unary()?;
                 Ok(!val)
             }
-            _ => self.parse_primary(),
+            Some(op @ (&ArithToken::PlusPlus | &ArithToken::MinusMinus)) => {
+                let delta = if *op == ArithToken::PlusPlus { 1 } else { -1 };
+                self.advance();
+                if !matches!(self.peek(), Some(ArithToken::Ident(_))) {
+                    // `--5` is a double negation and `++5` a double plus
+                    return self.parse_unary();
+                }
+                let name = self.parse_lvalue()?;
+                let value = self.var_value(&name)?.wrapping_add(delta);
+                self.store(&name, value);
+                Ok(value)
+            }
+            _ => self.parse_postfix(),
         }
     }
 
+    fn parse_postfix(&mut self) -> Result<i64> {
+        if !matches!(self.peek(), Some(ArithToken::Ident(_))) {
+            return self.parse_primary();
+        }
+        let name = self.parse_lvalue()?;
+        let value = self.var_value(&name)?;
+        let delta = match self.peek() {
+            Some(ArithToken::PlusPlus) => 1,
+            Some(ArithToken::MinusMinus) => -1,
+            _ => return Ok(value),
+        };
+        self.advance();
+        self.store(&name, value.wrapping_add(delta));
+        Ok(value)
+    }
+
     fn parse_primary(&mut self) -> Result<i64> {
         match self.advance() {
             Some(ArithToken::Number(n)) => Ok(*n),
-            Some(ArithToken::Ident(name)) => {
-                let name = name.clone();
-                Ok(self.var_value(&name))
-            }
             Some(ArithToken::LParen) => {
                 let val = self.parse_comma()?;
                 self.expect(&ArithToken::RParen)?;
                 Ok(val)
             }
-            _ => Err(VshError::Syntax(String::from(
+            _ => Err(Error::Syntax(String::from(
                 "expected number, variable, or '(' in arithmetic",
             ))),
         }
     }
+
+    /// Apply a binary operator, or the operator of a compound assignment.
+    fn apply_binary(&self, op: &ArithToken, lhs: i64, rhs: i64) -> Result<i64> {
+        Ok(match op {
+            ArithToken::Plus | ArithToken::PlusEq => lhs.wrapping_add(rhs),
+            ArithToken::Minus | ArithToken::MinusEq => lhs.wrapping_sub(rhs),
+            ArithToken::Star | ArithToken::StarEq => lhs.wrapping_mul(rhs),
+            ArithToken::Slash
+            | ArithToken::SlashEq
+            | ArithToken::Percent
+            | ArithToken::PercentEq => {
+                if rhs == 0 {
+                    if self.noeval > 0 {
+                        return Ok(0);
+                    }
+                    return Err(Error::DivisionByZero);
+                }
+                if matches!(op, ArithToken::Slash | ArithToken::SlashEq) {
+                    lhs.wrapping_div(rhs)
+                } else {
+                    lhs.wrapping_rem(rhs)
+                }
+            }
+            ArithToken::LShiftEq => lhs.wrapping_shl(rhs as u32),
+            ArithToken::RShiftEq => lhs.wrapping_shr(rhs as u32),
+            ArithToken::AmpEq => lhs & rhs,
+            ArithToken::CaretEq => lhs ^ rhs,
+            ArithToken::BarEq => lhs | rhs,
+            _ => rhs,
+        })
+    }
+}
+
+fn is_assign_op(tok: &ArithToken) -> bool {
+    matches!(
+        tok,
+        ArithToken::Eq
+            | ArithToken::PlusEq
+            | ArithToken::MinusEq
+            | ArithToken::StarEq
+            | ArithToken::SlashEq
+            | ArithToken::PercentEq
+            | ArithToken::LShiftEq
+            | ArithToken::RShiftEq
+            | ArithToken::AmpEq
+            | ArithToken::CaretEq
+            | ArithToken::BarEq
+    )
 }
 
 /// Integer exponentiation.
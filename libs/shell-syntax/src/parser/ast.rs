@@ -93,8 +93,10 @@ impl Word {
     pub fn new(raw: String) -> Self {
         Self { raw }
     }
+}
 
-    pub fn from_str(s: &str) -> Self {
+impl From<&str> for Word {
+    fn from(s: &str) -> Self {
         Self {
             raw: String::from(s),
         }
@@ -150,8 +152,9 @@ pub enum RedirectTarget {
     Fd(i32),
     /// Close the fd (`>&-` or `<&-`).
     Close,
-    /// A here-document body.
-    HereDocBody(String),
+    /// A here-document body, and whether its delimiter was quoted (no
+    /// expansion).
+    HereDocBody(String, bool),
     /// A here-string value.
     HereString(Word),
 }
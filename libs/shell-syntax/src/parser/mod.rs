@@ -1,4 +1,4 @@
-//! Recursive descent parser for the vsh shell.
+//! Recursive descent shell parser.
 //!
 //! Transforms a token stream from the lexer into an AST ([`ast::Program`]).
 
@@ -14,7 +14,7 @@ use ast::*;
 use redirect::{is_redirect_op, parse_redirect};
 
 use crate::{
-    error::{Result, VshError},
+    error::{Error, Result},
     lexer::token::{Span, Token, TokenKind},
 };
 
@@ -85,7 +85,7 @@ impl Parser {
             self.advance();
             Ok(())
         } else {
-            Err(VshError::Syntax(alloc::format!(
+            Err(Error::Syntax(alloc::format!(
                 "expected {:?}, got {:?}",
                 expected,
                 actual
@@ -294,6 +294,21 @@ impl Parser {
         let op_kind = self.peek().clone();
         self.advance(); // consume operator
 
+        // Here-document: the lexer put the collected body right after `<<`
+        if matches!(op_kind, TokenKind::DLess | TokenKind::DLessDash) {
+            let target = match self.peek().clone() {
+                TokenKind::HereDocBody(body, quoted) => {
+                    self.advance();
+                    RedirectTarget::HereDocBody(body, quoted)
+                }
+                _ => RedirectTarget::HereDocBody(String::new(), false),
+            };
+            let mut redir = parse_redirect(&op_kind, fd, None)?
+                .ok_or_else(|| Error::Syntax(String::from("invalid redirection")))?;
+            redir.target = target;
+            return Ok(redir);
+        }
+
         // Get the target word
         let target_word = match self.peek() {
             TokenKind::Word(w) => {
@@ -307,7 +322,7 @@ impl Parser {
         let target_str = target_word.as_deref();
         match parse_redirect(&op_kind, fd, target_str)? {
             Some(redir) => Ok(redir),
-            None => Err(VshError::Syntax(String::from("invalid redirection"))),
+            None => Err(Error::Syntax(String::from("invalid redirection"))),
         }
     }
 
@@ -397,7 +412,7 @@ impl Parser {
                 w
             }
             _ => {
-                return Err(VshError::Syntax(String::from(
+                return Err(Error::Syntax(String::from(
                     "expected variable name after 'for'",
                 )))
             }
@@ -453,7 +468,7 @@ impl Parser {
                 self.advance();
                 Word::new(w)
             }
-            _ => return Err(VshError::Syntax(String::from("expected word after 'case'"))),
+            _ => return Err(Error::Syntax(String::from("expected word after 'case'"))),
         };
 
         self.skip_newlines();
@@ -531,7 +546,7 @@ impl Parser {
                 w
             }
             _ => {
-                return Err(VshError::Syntax(String::from(
+                return Err(Error::Syntax(String::from(
                     "expected variable name after 'select'",
                 )))
             }
@@ -655,7 +670,7 @@ impl Parser {
                 self.advance();
                 w
             }
-            _ => return Err(VshError::Syntax(String::from("expected function name"))),
+            _ => return Err(Error::Syntax(String::from("expected function name"))),
         };
 
         self.expect(&TokenKind::LParen)?;
@@ -680,7 +695,7 @@ impl Parser {
                 w
             }
             _ => {
-                return Err(VshError::Syntax(String::from(
+                return Err(Error::Syntax(String::from(
                     "expected function name after 'function'",
                 )))
             }
@@ -807,3 +822,153 @@ impl TokenKind {
         matches!(self, TokenKind::Newline | TokenKind::Eof | TokenKind::Semi)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(input: &str) -> AndOrList {
+        let mut program = parse_input(input).unwrap();
+        assert_eq!(program.commands.len(), 1);
+        program.commands.remove(0).list
+    }
+
+    fn simple(cmd: &Command) -> &SimpleCommand {
+        match cmd {
+            Command::Simple(simple) => simple,
+            other => panic!("not a simple command: {:?}", other),
+        }
+    }
+
+    fn words(cmd: &SimpleCommand) -> Vec<&str> {
+        cmd.words.iter().map(|w| w.raw.as_str()).collect()
+    }
+
+    #[test]
+    fn test_simple_command() {
+        let list = parse_one("FOO=1 echo 'a b' \"$x\" > out 2>&1");
+        let cmd = simple(&list.first.commands[0]);
+        assert_eq!(cmd.assignments[0].name, "FOO");
+        assert_eq!(cmd.assignments[0].value.raw, "1");
+        assert_eq!(words(cmd), ["echo", "'a b'", "\"$x\""]);
+        assert_eq!(cmd.redirects.len(), 2);
+        assert_eq!(cmd.redirects[0].op, RedirectOp::Output);
+        assert_eq!(
+            cmd.redirects[0].target,
+            RedirectTarget::File(Word::from("out"))
+        );
+        assert_eq!(cmd.redirects[1].fd, Some(2));
+        assert_eq!(cmd.redirects[1].op, RedirectOp::DupOutput);
+    }
+
+    #[test]
+    fn test_pipeline_and_or_list() {
+        let list = parse_one("! a | b && c || d");
+        assert!(list.first.negated);
+        assert_eq!(list.first.commands.len(), 2);
+        assert_eq!(words(simple(&list.first.commands[1])), ["b"]);
+        assert_eq!(list.rest.len(), 2);
+        assert_eq!(list.rest[0].0, AndOrOp::And);
+        assert_eq!(list.rest[1].0, AndOrOp::Or);
+    }
+
+    #[test]
+    fn test_command_list_and_background() {
+        let program = parse_input("a; b &\nc").unwrap();
+        assert_eq!(program.commands.len(), 3);
+        assert!(!program.commands[0].background);
+        assert!(program.commands[1].background);
+    }
+
+    #[test]
+    fn test_if_and_for() {
+        let list = parse_one("if true; then echo y; elif false; then :; else echo n; fi");
+        match &list.first.commands[0] {
+            Command::Compound(CompoundCommand::If(clause), _) => {
+                assert_eq!(clause.branches.len(), 2);
+                assert!(clause.else_body.is_some());
+            }
+            other => panic!("not an if: {:?}", other),
+        }
+
+        let list = parse_one("for f in *.txt a; do echo $f; done");
+        match &list.first.commands[0] {
+            Command::Compound(CompoundCommand::For(clause), _) => {
+                assert_eq!(clause.var, "f");
+                let raw: Vec<&str> = clause
+                    .words
+                    .iter()
+                    .flatten()
+                    .map(|w| w.raw.as_str())
+                    .collect();
+                assert_eq!(raw, ["*.txt", "a"]);
+                assert_eq!(clause.body.len(), 1);
+            }
+            other => panic!("not a for loop: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_case() {
+        let list = parse_one("case $x in a|b) echo ab;; *) echo other;& esac");
+        match &list.first.commands[0] {
+            Command::Compound(CompoundCommand::Case(clause), _) => {
+                assert_eq!(clause.word.raw, "$x");
+                assert_eq!(clause.items.len(), 2);
+                assert_eq!(clause.items[0].patterns.len(), 2);
+                assert_eq!(clause.items[0].terminator, CaseTerminator::Break);
+                assert_eq!(clause.items[1].terminator, CaseTerminator::FallThrough);
+            }
+            other => panic!("not a case: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_function_definition() {
+        let list = parse_one("greet() { echo hi; }");
+        match &list.first.commands[0] {
+            Command::FunctionDef(def) => {
+                assert_eq!(def.name, "greet");
+                assert!(matches!(
+                    *def.body,
+                    Command::Compound(CompoundCommand::BraceGroup(_), _)
+                ));
+            }
+            other => panic!("not a function: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_here_document() {
+        let list = parse_one("cat <<EOF\nhello $USER\nEOF\n");
+        let cmd = simple(&list.first.commands[0]);
+        assert_eq!(cmd.redirects[0].op, RedirectOp::HereDoc);
+        assert_eq!(
+            cmd.redirects[0].target,
+            RedirectTarget::HereDocBody(String::from("hello $USER\n"), false)
+        );
+    }
+
+    #[test]
+    fn test_conditional_expression() {
+        let list = parse_one("[[ -f $f && $x == a* ]]");
+        match &list.first.commands[0] {
+            Command::Compound(CompoundCommand::ConditionalExpr(ConditionalExpr::And(l, r)), _) => {
+                assert!(matches!(**l, ConditionalExpr::Unary(ref op, _) if op == "-f"));
+                assert!(matches!(**r, ConditionalExpr::Binary(_, ref op, _) if op == "=="));
+            }
+            other => panic!("not a [[ && ]]: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for input in ["if true; then echo", "for do", "case x in", "( echo"] {
+            assert!(
+                matches!(parse_input(input), Err(Error::Syntax(_))),
+                "{:?} parsed",
+                input
+            );
+        }
+    }
+}
@@ -6,7 +6,7 @@
 use alloc::string::String;
 
 use crate::{
-    error::{Result, VshError},
+    error::{Error, Result},
     lexer::token::TokenKind,
     parser::ast::{Redirect, RedirectOp, RedirectTarget, Word},
 };
@@ -43,8 +43,8 @@ pub fn parse_redirect(
     let target = match op {
         RedirectOp::HereDoc | RedirectOp::HereDocStrip => {
             // The delimiter word was already consumed by the lexer.
-            // The body will be filled in during here-doc collection.
-            RedirectTarget::HereDocBody(String::new())
+            // The parser fills in the body from the HereDocBody token.
+            RedirectTarget::HereDocBody(String::new(), false)
         }
         RedirectOp::DupOutput | RedirectOp::DupInput => match next_word {
             Some("-") => RedirectTarget::Close,
@@ -52,25 +52,25 @@ pub fn parse_redirect(
                 if let Ok(n) = parse_fd_number(w) {
                     RedirectTarget::Fd(n)
                 } else {
-                    RedirectTarget::File(Word::from_str(w))
+                    RedirectTarget::File(Word::from(w))
                 }
             }
             None => {
-                return Err(VshError::Syntax(String::from(
+                return Err(Error::Syntax(String::from(
                     "expected file descriptor or '-' after redirect",
                 )));
             }
         },
         RedirectOp::HereString => match next_word {
-            Some(w) => RedirectTarget::HereString(Word::from_str(w)),
+            Some(w) => RedirectTarget::HereString(Word::from(w)),
             None => {
-                return Err(VshError::Syntax(String::from("expected word after <<<")));
+                return Err(Error::Syntax(String::from("expected word after <<<")));
             }
         },
         _ => match next_word {
-            Some(w) => RedirectTarget::File(Word::from_str(w)),
+            Some(w) => RedirectTarget::File(Word::from(w)),
             None => {
-                return Err(VshError::Syntax(String::from(
+                return Err(Error::Syntax(String::from(
                     "expected filename after redirect",
                 )));
             }
@@ -45,7 +45,7 @@ fn parse_not_expr(words: &[String], pos: &mut usize) -> ConditionalExpr {
 
 fn parse_primary_expr(words: &[String], pos: &mut usize) -> ConditionalExpr {
     if *pos >= words.len() {
-        return ConditionalExpr::Word(Word::from_str(""));
+        return ConditionalExpr::Word(Word::from(""));
     }
 
     // Parenthesized group
@@ -58,16 +58,8 @@ fn parse_primary_expr(words: &[String], pos: &mut usize) -> ConditionalExpr {
         return ConditionalExpr::Group(Box::new(expr));
     }
 
-    // Unary operators: -f, -d, -e, -z, -n, etc.
-    if words[*pos].starts_with('-') && words[*pos].len() == 2 && *pos + 1 < words.len() {
-        let op = words[*pos].clone();
-        *pos += 1;
-        let operand = words[*pos].clone();
-        *pos += 1;
-        return ConditionalExpr::Unary(op, Word::from_str(&operand));
-    }
-
-    // Binary operators: word OP word
+    // Binary operators: word OP word (before unary ones, so that in
+    // `-5 -lt 3` the `-5` is an operand)
     if *pos + 2 < words.len() {
         let lhs = words[*pos].clone();
         let op = &words[*pos + 1];
@@ -78,16 +70,25 @@ fn parse_primary_expr(words: &[String], pos: &mut usize) -> ConditionalExpr {
             let rhs = words[*pos].clone();
             *pos += 1;
             if op == "=~" {
-                return ConditionalExpr::Regex(Word::from_str(&lhs), Word::from_str(&rhs));
+                return ConditionalExpr::Regex(Word::new(lhs), Word::new(rhs));
             }
-            return ConditionalExpr::Binary(Word::from_str(&lhs), op, Word::from_str(&rhs));
+            return ConditionalExpr::Binary(Word::new(lhs), op, Word::new(rhs));
         }
     }
 
+    // Unary operators: -f, -d, -e, -z, -n, etc.
+    if words[*pos].starts_with('-') && words[*pos].len() == 2 && *pos + 1 < words.len() {
+        let op = words[*pos].clone();
+        *pos += 1;
+        let operand = words[*pos].clone();
+        *pos += 1;
+        return ConditionalExpr::Unary(op, Word::new(operand));
+    }
+
     // Bare word (treated as -n word)
     let w = words[*pos].clone();
     *pos += 1;
-    ConditionalExpr::Word(Word::from_str(&w))
+    ConditionalExpr::Word(Word::new(w))
 }
 
 fn is_binary_op(op: &str) -> bool {
@@ -107,6 +107,8 @@ pub fn remove_quotes(word: &str) -> String {
 ///
 /// Default IFS is space, tab, newline. Leading/trailing IFS chars are
 /// trimmed, and sequences of IFS chars between words are collapsed.
+/// Backslash-escaped and quoted characters never split; they are kept,
+/// with their quoting, for quote removal.
 pub fn word_split(value: &str, ifs: &str) -> Vec<String> {
     if value.is_empty() {
         return Vec::new();
@@ -136,6 +138,31 @@ pub fn word_split(value: &str, ifs: &str) -> Vec<String> {
     while i < chars.len() {
         let ch = chars[i];
 
+        if ch == '\\' && i + 1 < chars.len() {
+            current.push(ch);
+            current.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if ch == '\'' || ch == '"' {
+            // Copy the quoted run, quotes included
+            current.push(ch);
+            i += 1;
+            while i < chars.len() && chars[i] != ch {
+                if ch == '"' && chars[i] == '\\' && i + 1 < chars.len() {
+                    current.push(chars[i]);
+                    i += 1;
+                }
+                current.push(chars[i]);
+                i += 1;
+            }
+            if i < chars.len() {
+                current.push(ch);
+                i += 1;
+            }
+            continue;
+        }
+
         if is_ifs(ch) {
             if !current.is_empty() {
                 words.push(core::mem::take(&mut current));
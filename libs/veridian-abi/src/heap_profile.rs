@@ -0,0 +1,211 @@
+//! Userland heap profiling.
+//!
+//! [`HeapStats`] is `struct veridian_heap_stats` of
+//! `<veridian/heap_profile.h>`: the counters an allocator keeps while
+//! `MALLOC_PROFILE` is set, registered with [`crate::nr::SYS_HEAP_PROFILE`]
+//! so the kernel can read them out of the process for `/proc/<pid>/heap`.
+//! The fields are atomics so a Rust allocator can update them in place;
+//! they have the layout of the plain integers the C header declares.
+
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+/// `HeapStats::mode`: not profiling.
+pub const MODE_OFF: u32 = 0;
+/// `HeapStats::mode`: size classes and live/peak bytes.
+pub const MODE_COUNTS: u32 = 1;
+/// `HeapStats::mode`: also the call sites of sampled allocations.
+pub const MODE_SITES: u32 = 2;
+
+/// `HeapStats::magic` ("VHPF").
+pub const MAGIC: u32 = 0x5648_5046;
+/// `HeapStats::version`.
+pub const VERSION: u32 = 1;
+
+/// Number of size classes: class 0 holds sizes up to 16 bytes, class `n`
+/// sizes up to `16 << n`, and the last one everything larger.
+pub const CLASSES: usize = 24;
+/// Call sites in [`HeapStats`].
+pub const SITES: usize = 64;
+/// Default allocations per sample in [`MODE_SITES`].
+pub const INTERVAL: u32 = 32;
+
+/// Size class of an allocation of `size` bytes.
+pub const fn size_class(size: usize) -> usize {
+    if size <= 16 {
+        return 0;
+    }
+    let bits = (usize::BITS - (size - 1).leading_zeros()) as usize;
+    if bits - 4 < CLASSES {
+        bits - 4
+    } else {
+        CLASSES - 1
+    }
+}
+
+/// The mode `MALLOC_PROFILE` asks for: `counts`, `sites` or `sites:N`.
+pub fn parse_mode(value: &[u8]) -> Option<u32> {
+    match value {
+        b"counts" => Some(MODE_COUNTS),
+        b"sites" => Some(MODE_SITES),
+        _ if value.starts_with(b"sites:") => Some(MODE_SITES),
+        _ => None,
+    }
+}
+
+#[repr(C)]
+pub struct HeapClass {
+    pub allocs: AtomicU64,
+    pub frees: AtomicU64,
+    pub bytes: AtomicU64,
+}
+
+#[repr(C)]
+pub struct HeapSite {
+    /// Return address into the caller of the allocator, 0 for a free slot.
+    pub pc: AtomicU64,
+    pub allocs: AtomicU64,
+    pub bytes: AtomicU64,
+    pub live_bytes: AtomicU64,
+}
+
+/// `struct veridian_heap_stats`.
+#[repr(C)]
+pub struct HeapStats {
+    pub magic: AtomicU32,
+    pub version: AtomicU32,
+    pub mode: AtomicU32,
+    pub sample_interval: AtomicU32,
+    pub allocs: AtomicU64,
+    pub frees: AtomicU64,
+    pub live_bytes: AtomicU64,
+    pub peak_bytes: AtomicU64,
+    /// Samples that found no free site slot.
+    pub dropped: AtomicU64,
+    pub classes: [HeapClass; CLASSES],
+    pub sites: [HeapSite; SITES],
+}
+
+impl Default for HeapStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeapStats {
+    /// Statistics with profiling off, for a `static`.
+    pub const fn new() -> Self {
+        HeapStats {
+            magic: AtomicU32::new(0),
+            version: AtomicU32::new(0),
+            mode: AtomicU32::new(MODE_OFF),
+            sample_interval: AtomicU32::new(INTERVAL),
+            allocs: AtomicU64::new(0),
+            frees: AtomicU64::new(0),
+            live_bytes: AtomicU64::new(0),
+            peak_bytes: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            classes: [const {
+                HeapClass {
+                    allocs: AtomicU64::new(0),
+                    frees: AtomicU64::new(0),
+                    bytes: AtomicU64::new(0),
+                }
+            }; CLASSES],
+            sites: [const {
+                HeapSite {
+                    pc: AtomicU64::new(0),
+                    allocs: AtomicU64::new(0),
+                    bytes: AtomicU64::new(0),
+                    live_bytes: AtomicU64::new(0),
+                }
+            }; SITES],
+        }
+    }
+
+    /// Start counting in `mode`. The caller then registers `self`.
+    pub fn enable(&self, mode: u32) {
+        self.magic.store(MAGIC, Ordering::Relaxed);
+        self.version.store(VERSION, Ordering::Relaxed);
+        self.mode.store(mode, Ordering::Release);
+    }
+
+    /// Whether counting is on.
+    pub fn enabled(&self) -> bool {
+        self.mode.load(Ordering::Relaxed) != MODE_OFF
+    }
+
+    /// Count an allocation of `size` bytes.
+    pub fn count_alloc(&self, size: usize) {
+        let class = &self.classes[size_class(size)];
+        class.allocs.fetch_add(1, Ordering::Relaxed);
+        class.bytes.fetch_add(size as u64, Ordering::Relaxed);
+        self.allocs.fetch_add(1, Ordering::Relaxed);
+        let live = self.live_bytes.fetch_add(size as u64, Ordering::Relaxed) + size as u64;
+        self.peak_bytes.fetch_max(live, Ordering::Relaxed);
+    }
+
+    /// Count a free of `size` bytes.
+    pub fn count_free(&self, size: usize) {
+        self.classes[size_class(size)]
+            .frees
+            .fetch_add(1, Ordering::Relaxed);
+        self.frees.fetch_add(1, Ordering::Relaxed);
+        self.live_bytes.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_class() {
+        assert_eq!(size_class(0), 0);
+        assert_eq!(size_class(16), 0);
+        assert_eq!(size_class(17), 1);
+        assert_eq!(size_class(4096), 8);
+        assert_eq!(size_class(4097), 9);
+        assert_eq!(size_class(usize::MAX), CLASSES - 1);
+    }
+
+    #[test]
+    fn test_counts() {
+        let stats = HeapStats::new();
+        stats.count_alloc(100);
+        stats.count_alloc(20);
+        stats.count_free(100);
+        assert_eq!(stats.live_bytes.load(Ordering::Relaxed), 20);
+        assert_eq!(stats.peak_bytes.load(Ordering::Relaxed), 120);
+        assert_eq!(stats.classes[3].frees.load(Ordering::Relaxed), 1);
+        assert_eq!(parse_mode(b"sites:8"), Some(MODE_SITES));
+        assert_eq!(parse_mode(b"yes"), None);
+    }
+
+    #[test]
+    fn test_layout_matches_header() {
+        extern crate std;
+
+        let header = include_str!("../../../userland/libc/include/veridian/heap_profile.h");
+        let define = |name: &str| -> u64 {
+            let line = header
+                .lines()
+                .find(|l| l.starts_with(&std::format!("#define {} ", name)))
+                .unwrap();
+            let value = line.split_whitespace().nth(2).unwrap();
+            match value.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex.trim_end_matches('u'), 16).unwrap(),
+                None => value.parse().unwrap(),
+            }
+        };
+        assert_eq!(define("HEAP_PROFILE_MAGIC"), MAGIC as u64);
+        assert_eq!(define("HEAP_PROFILE_VERSION"), VERSION as u64);
+        assert_eq!(define("HEAP_PROFILE_CLASSES"), CLASSES as u64);
+        assert_eq!(define("HEAP_PROFILE_SITES_MAX"), SITES as u64);
+        assert_eq!(define("HEAP_PROFILE_INTERVAL"), INTERVAL as u64);
+        assert_eq!(define("HEAP_PROFILE_SITES"), MODE_SITES as u64);
+        assert_eq!(
+            core::mem::size_of::<HeapStats>(),
+            56 + 24 * CLASSES + 32 * SITES
+        );
+    }
+}
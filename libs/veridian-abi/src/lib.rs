@@ -0,0 +1,545 @@
+//! VeridianOS system call ABI.
+//!
+//! The syscall table below is the single definition of every system call
+//! number. The kernel's `Syscall` enum and its `TryFrom<usize>` decoding,
+//! the `SYS_*` constants userland passes in the syscall number register,
+//! and the names used in traces are all generated from it, and the tests
+//! check `<veridian/syscall.h>` against it.
+//!
+//! Numbers are append-only: a released number is never reused or given a
+//! different meaning. Adding a system call bumps [`ABI_VERSION`]'s minor
+//! version; changing or removing one bumps the major version. A binary
+//! asks the kernel for its version with [`nr::SYS_ABI_VERSION`] at startup
+//! and runs only if [`AbiVersion::supports`] accepts it.
+//!
+//! [`heap_profile`] holds the layout of the heap statistics a process
+//! registers with [`nr::SYS_HEAP_PROFILE`], and [`syscall_filter`] the
+//! modes, actions and syscall groups of [`nr::SYS_SYSCALL_FILTER`].
+//!
+//! The crate is `no_std` and has no dependencies.
+
+#![no_std]
+
+pub mod heap_profile;
+pub mod syscall_filter;
+
+/// A syscall ABI version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AbiVersion {
+    /// Bumped when a system call changes meaning or is removed.
+    pub major: u16,
+    /// Bumped when system calls are added.
+    pub minor: u16,
+}
+
+/// The ABI this crate describes.
+pub const ABI_VERSION: AbiVersion = AbiVersion::new(1, 5);
+
+impl AbiVersion {
+    /// The version `major.minor`.
+    pub const fn new(major: u16, minor: u16) -> AbiVersion {
+        AbiVersion { major, minor }
+    }
+
+    /// Whether a kernel implementing this ABI can run a binary built
+    /// against `required`.
+    pub const fn supports(self, required: AbiVersion) -> bool {
+        self.major == required.major && self.minor >= required.minor
+    }
+
+    /// The version as passed to and returned by `SYS_ABI_VERSION`.
+    pub const fn to_raw(self) -> usize {
+        (self.major as usize) << 16 | self.minor as usize
+    }
+
+    /// Decode a `SYS_ABI_VERSION` argument or return value.
+    pub const fn from_raw(raw: usize) -> AbiVersion {
+        AbiVersion::new((raw >> 16) as u16, raw as u16)
+    }
+}
+
+macro_rules! syscall_table {
+    ($($(#[$doc:meta])* $variant:ident = $nr:literal => $sys:ident,)*) => {
+        /// System call numbers
+        #[repr(usize)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Syscall {
+            $($(#[$doc])* $variant = $nr,)*
+        }
+
+        /// `SYS_*` constants, named as in `<veridian/syscall.h>`.
+        pub mod nr {
+            $(pub const $sys: usize = $nr;)*
+        }
+
+        impl Syscall {
+            /// Every system call, in table order.
+            pub const ALL: &'static [Syscall] = &[$(Syscall::$variant),*];
+
+            /// The number passed in the syscall number register.
+            pub const fn number(self) -> usize {
+                self as usize
+            }
+
+            /// The `SYS_*` name of this system call.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Syscall::$variant => stringify!($sys),)*
+                }
+            }
+        }
+
+        impl TryFrom<usize> for Syscall {
+            type Error = ();
+
+            fn try_from(value: usize) -> Result<Self, Self::Error> {
+                match value {
+                    $($nr => Ok(Syscall::$variant),)*
+                    _ => Err(()),
+                }
+            }
+        }
+    };
+}
+
+syscall_table! {
+    // IPC system calls
+    IpcSend = 0 => SYS_IPC_SEND,
+    IpcReceive = 1 => SYS_IPC_RECEIVE,
+    IpcCall = 2 => SYS_IPC_CALL,
+    IpcReply = 3 => SYS_IPC_REPLY,
+    IpcCreateEndpoint = 4 => SYS_IPC_CREATE_ENDPOINT,
+    IpcBindEndpoint = 5 => SYS_IPC_BIND_ENDPOINT,
+    IpcShareMemory = 6 => SYS_IPC_SHARE_MEMORY,
+    IpcMapMemory = 7 => SYS_IPC_MAP_MEMORY,
+    IpcLookupEndpoint = 8 => SYS_IPC_LOOKUP_ENDPOINT,
+
+    // Process management
+    ProcessYield = 10 => SYS_PROCESS_YIELD,
+    ProcessExit = 11 => SYS_PROCESS_EXIT,
+    ProcessFork = 12 => SYS_PROCESS_FORK,
+    ProcessExec = 13 => SYS_PROCESS_EXEC,
+    ProcessWait = 14 => SYS_PROCESS_WAIT,
+    ProcessGetPid = 15 => SYS_PROCESS_GETPID,
+    ProcessGetPPid = 16 => SYS_PROCESS_GETPPID,
+    ProcessSetPriority = 17 => SYS_PROCESS_SETPRIORITY,
+    ProcessGetPriority = 18 => SYS_PROCESS_GETPRIORITY,
+
+    // Thread management
+    ThreadCreate = 40 => SYS_THREAD_CREATE,
+    ThreadExit = 41 => SYS_THREAD_EXIT,
+    ThreadJoin = 42 => SYS_THREAD_JOIN,
+    ThreadGetTid = 43 => SYS_THREAD_GETTID,
+    ThreadSetAffinity = 44 => SYS_THREAD_SET_AFFINITY,
+    ThreadGetAffinity = 45 => SYS_THREAD_GET_AFFINITY,
+    ThreadClone = 46 => SYS_THREAD_CLONE,
+
+    // Memory management
+    MemoryMap = 20 => SYS_MEMORY_MAP,
+    MemoryUnmap = 21 => SYS_MEMORY_UNMAP,
+    MemoryProtect = 22 => SYS_MEMORY_PROTECT,
+    MemoryBrk = 23 => SYS_MEMORY_BRK,
+
+    // Capability management
+    CapabilityGrant = 30 => SYS_CAPABILITY_GRANT,
+    CapabilityRevoke = 31 => SYS_CAPABILITY_REVOKE,
+    CapabilityInspect = 32 => SYS_CAP_INSPECT,
+    AuditRead = 33 => SYS_AUDIT_READ,
+
+    // Filesystem operations
+    FileOpen = 50 => SYS_FILE_OPEN,
+    FileClose = 51 => SYS_FILE_CLOSE,
+    FileRead = 52 => SYS_FILE_READ,
+    FileWrite = 53 => SYS_FILE_WRITE,
+    FileSeek = 54 => SYS_FILE_SEEK,
+    FileStat = 55 => SYS_FILE_STAT,
+    FileTruncate = 56 => SYS_FILE_TRUNCATE,
+
+    // Directory operations
+    DirMkdir = 60 => SYS_DIR_MKDIR,
+    DirRmdir = 61 => SYS_DIR_RMDIR,
+    DirOpendir = 62 => SYS_DIR_OPENDIR,
+    DirReaddir = 63 => SYS_DIR_READDIR,
+    DirClosedir = 64 => SYS_DIR_CLOSEDIR,
+    FilePipe2 = 65 => SYS_FILE_PIPE2,
+    FileDup3 = 66 => SYS_FILE_DUP3,
+
+    // Filesystem management
+    FsMount = 70 => SYS_FS_MOUNT,
+    FsUnmount = 71 => SYS_FS_UNMOUNT,
+    FsSync = 72 => SYS_FS_SYNC,
+    FsFsync = 73 => SYS_FS_FSYNC,
+
+    // Kernel information
+    KernelGetInfo = 80 => SYS_KERNEL_GET_INFO,
+    Dmesg = 81 => SYS_DMESG,
+    TraceCtl = 82 => SYS_TRACE_CTL,
+    CrashDump = 83 => SYS_CRASH_DUMP,
+    Watchdog = 84 => SYS_WATCHDOG,
+    Reboot = 85 => SYS_REBOOT,
+    /// Report the kernel's ABI version and check a binary's against it.
+    AbiVersion = 86 => SYS_ABI_VERSION,
+
+    // Package management
+    PkgInstall = 90 => SYS_PKG_INSTALL,
+    PkgRemove = 91 => SYS_PKG_REMOVE,
+    PkgQuery = 92 => SYS_PKG_QUERY,
+    PkgList = 93 => SYS_PKG_LIST,
+    PkgUpdate = 94 => SYS_PKG_UPDATE,
+    PkgVerify = 95 => SYS_PKG_VERIFY,
+
+    // A/B kernel boot slots
+    BootSlotInstall = 96 => SYS_BOOT_SLOT_INSTALL,
+    BootSlotCommit = 97 => SYS_BOOT_SLOT_COMMIT,
+    BootSlotQuery = 98 => SYS_BOOT_SLOT_QUERY,
+
+    // Extended filesystem operations
+    FileDup = 57 => SYS_FILE_DUP,
+    FileDup2 = 58 => SYS_FILE_DUP2,
+    FilePipe = 59 => SYS_FILE_PIPE,
+
+    // Time management
+    TimeGetUptime = 100 => SYS_TIME_GET_UPTIME,
+    TimeCreateTimer = 101 => SYS_TIME_CREATE_TIMER,
+    TimeCancelTimer = 102 => SYS_TIME_CANCEL_TIMER,
+
+    // Extended process operations
+    ProcessGetcwd = 110 => SYS_PROCESS_GETCWD,
+    ProcessChdir = 111 => SYS_PROCESS_CHDIR,
+    FileIoctl = 112 => SYS_FILE_IOCTL,
+    ProcessKill = 113 => SYS_PROCESS_KILL,
+
+    // Signal management
+    SigAction = 120 => SYS_SIGACTION,
+    SigProcmask = 121 => SYS_SIGPROCMASK,
+    SigSuspend = 122 => SYS_SIGSUSPEND,
+    SigReturn = 123 => SYS_SIGRETURN,
+
+    // POSIX time syscalls
+    ClockGettime = 160 => SYS_CLOCK_GETTIME,
+    ClockGetres = 161 => SYS_CLOCK_GETRES,
+    Nanosleep = 162 => SYS_NANOSLEEP,
+    Gettimeofday = 163 => SYS_GETTIMEOFDAY,
+
+    // Identity syscalls
+    Getuid = 170 => SYS_GETUID,
+    Geteuid = 171 => SYS_GETEUID,
+    Getgid = 172 => SYS_GETGID,
+    Getegid = 173 => SYS_GETEGID,
+    Setuid = 174 => SYS_SETUID,
+    Setgid = 175 => SYS_SETGID,
+
+    // Process group / session syscalls
+    Setpgid = 176 => SYS_SETPGID,
+    Getpgid = 177 => SYS_GETPGID,
+    Getpgrp = 178 => SYS_GETPGRP,
+    Setsid = 179 => SYS_SETSID,
+    Getsid = 180 => SYS_GETSID,
+
+    // Supplementary groups
+    Getgroups = 181 => SYS_GETGROUPS,
+    Setgroups = 182 => SYS_SETGROUPS,
+
+    // Scatter/gather I/O
+    Readv = 183 => SYS_READV,
+    Writev = 184 => SYS_WRITEV,
+
+    // Debug / tracing
+    Ptrace = 140 => SYS_PTRACE,
+
+    // Extended filesystem operations (Phase 4B)
+    FileStatPath = 150 => SYS_FILE_STAT_PATH,
+    FileLstat = 151 => SYS_FILE_LSTAT,
+    FileReadlink = 152 => SYS_FILE_READLINK,
+    FileAccess = 153 => SYS_FILE_ACCESS,
+    FileRename = 154 => SYS_FILE_RENAME,
+    FileLink = 155 => SYS_FILE_LINK,
+    FileSymlink = 156 => SYS_FILE_SYMLINK,
+    FileUnlink = 157 => SYS_FILE_UNLINK,
+    FileFcntl = 158 => SYS_FILE_FCNTL,
+
+    // New filesystem ops for self-hosting (Phase 4A)
+    FileChmod = 185 => SYS_FILE_CHMOD,
+    FileFchmod = 186 => SYS_FILE_FCHMOD,
+    ProcessUmask = 187 => SYS_PROCESS_UMASK,
+    FileTruncatePath = 188 => SYS_FILE_TRUNCATE_PATH,
+    FilePoll = 189 => SYS_FILE_POLL,
+    FileOpenat = 190 => SYS_FILE_OPENAT,
+    FileFstatat = 191 => SYS_FILE_FSTATAT,
+    FileUnlinkat = 192 => SYS_FILE_UNLINKAT,
+    FileMkdirat = 193 => SYS_FILE_MKDIRAT,
+    FileRenameat = 194 => SYS_FILE_RENAMEAT,
+    FilePread = 195 => SYS_FILE_PREAD,
+    FilePwrite = 196 => SYS_FILE_PWRITE,
+
+    // Ownership and device node syscalls
+    FileChown = 197 => SYS_FILE_CHOWN,
+    FileFchown = 198 => SYS_FILE_FCHOWN,
+    FileMknod = 199 => SYS_FILE_MKNOD,
+    FileSelect = 200 => SYS_FILE_SELECT,
+    FutexWait = 201 => SYS_FUTEX_WAIT,
+    FutexWake = 202 => SYS_FUTEX_WAKE,
+    ArchPrctl = 203 => SYS_ARCH_PRCTL,
+
+    // System information
+    ProcessUname = 204 => SYS_PROCESS_UNAME,
+    /// Look up an environment variable by name from the process's env_vars.
+    ///
+    /// Required because some CRT implementations (e.g. GCC's internal CRT)
+    /// skip __libc_start_main, leaving the libc `environ` pointer NULL.
+    ProcessGetenv = 205 => SYS_PROCESS_GETENV,
+
+    // POSIX shared memory
+    ShmOpen = 210 => SYS_SHM_OPEN,
+    ShmUnlink = 211 => SYS_SHM_UNLINK,
+    ShmTruncate = 212 => SYS_SHM_TRUNCATE,
+    ShmClose = 213 => SYS_SHM_CLOSE,
+
+    // Socket operations
+    SocketCreate = 220 => SYS_SOCKET_CREATE,
+    SocketBind = 221 => SYS_SOCKET_BIND,
+    SocketListen = 222 => SYS_SOCKET_LISTEN,
+    SocketConnect = 223 => SYS_SOCKET_CONNECT,
+    SocketAccept = 224 => SYS_SOCKET_ACCEPT,
+    SocketSend = 225 => SYS_SOCKET_SEND,
+    SocketRecv = 226 => SYS_SOCKET_RECV,
+    SocketClose = 227 => SYS_SOCKET_CLOSE,
+    SocketPair = 228 => SYS_SOCKET_PAIR,
+
+    // Graphics / framebuffer (Phase 6)
+    FbGetInfo = 230 => SYS_FB_GET_INFO,
+    FbMap = 231 => SYS_FB_MAP,
+    InputPoll = 232 => SYS_INPUT_POLL,
+    InputRead = 233 => SYS_INPUT_READ,
+    FbSwap = 234 => SYS_FB_SWAP,
+    Screenshot = 235 => SYS_SCREENSHOT,
+    Notify = 236 => SYS_NOTIFY,
+    Window = 237 => SYS_WINDOW,
+    InputInject = 238 => SYS_INPUT_INJECT,
+    FbFlip = 239 => SYS_FB_FLIP,
+
+    // Wayland compositor (Phase 6)
+    WlConnect = 240 => SYS_WL_CONNECT,
+    WlDisconnect = 241 => SYS_WL_DISCONNECT,
+    WlSendMessage = 242 => SYS_WL_SEND_MESSAGE,
+    WlRecvMessage = 243 => SYS_WL_RECV_MESSAGE,
+    WlCreateShmPool = 244 => SYS_WL_CREATE_SHM_POOL,
+    WlCreateSurface = 245 => SYS_WL_CREATE_SURFACE,
+    WlCommitSurface = 246 => SYS_WL_COMMIT_SURFACE,
+    WlGetEvents = 247 => SYS_WL_GET_EVENTS,
+
+    // Network (Phase 6) -- AF_INET extensions
+    NetSendTo = 250 => SYS_NET_SENDTO,
+    NetRecvFrom = 251 => SYS_NET_RECVFROM,
+    NetGetSockName = 252 => SYS_NET_GETSOCKNAME,
+    NetGetPeerName = 253 => SYS_NET_GETPEERNAME,
+    NetSetSockOpt = 254 => SYS_NET_SETSOCKOPT,
+    NetGetSockOpt = 255 => SYS_NET_GETSOCKOPT,
+    NetFirewall = 256 => SYS_NET_FIREWALL,
+    NetPacketRing = 257 => SYS_NET_PACKET_RING,
+
+    // Resource limits (Phase 6.5)
+    GetRlimit = 260 => SYS_GETRLIMIT,
+    SetRlimit = 261 => SYS_SETRLIMIT,
+
+    // epoll I/O multiplexing (Phase 6.5)
+    EpollCreate = 262 => SYS_EPOLL_CREATE,
+    EpollCtl = 263 => SYS_EPOLL_CTL,
+    EpollWait = 264 => SYS_EPOLL_WAIT,
+
+    // Process groups / sessions (Phase 6.5)
+    SetPgid = 270 => SYS_SET_PGID,
+    GetPgid = 271 => SYS_GET_PGID,
+    SetSid = 272 => SYS_SET_SID,
+    GetSid = 273 => SYS_GET_SID,
+    TcSetPgrp = 274 => SYS_TC_SET_PGRP,
+    TcGetPgrp = 275 => SYS_TC_GET_PGRP,
+
+    // PTY (Phase 6.5)
+    OpenPty = 280 => SYS_OPENPTY,
+    GrantPty = 281 => SYS_GRANTPT,
+    UnlockPty = 282 => SYS_UNLOCKPT,
+    PtsName = 283 => SYS_PTSNAME,
+
+    // Filesystem extensions (Phase 6.5)
+    Link = 290 => SYS_LINK,
+    Symlink = 291 => SYS_SYMLINK,
+    Readlink = 292 => SYS_READLINK,
+    Lstat = 293 => SYS_LSTAT,
+    Fchmod = 294 => SYS_FCHMOD,
+    Fchown = 295 => SYS_FCHOWN,
+    Umask = 296 => SYS_UMASK,
+    Access = 297 => SYS_ACCESS,
+
+    // Poll/fcntl (Phase 6.5)
+    Poll = 300 => SYS_POLL,
+    Fcntl = 301 => SYS_FCNTL,
+
+    // Threading (Phase 6.5)
+    Clone = 310 => SYS_CLONE,
+    Futex = 311 => SYS_FUTEX,
+
+    // Audio (Phase 7)
+    AudioOpen = 320 => SYS_AUDIO_OPEN,
+    AudioClose = 321 => SYS_AUDIO_CLOSE,
+    AudioWrite = 322 => SYS_AUDIO_WRITE,
+    AudioSetVolume = 323 => SYS_AUDIO_SET_VOLUME,
+    AudioGetInfo = 324 => SYS_AUDIO_GET_INFO,
+    AudioStart = 325 => SYS_AUDIO_START,
+    AudioStop = 326 => SYS_AUDIO_STOP,
+    AudioPause = 327 => SYS_AUDIO_PAUSE,
+
+    // musl libc compatibility syscalls
+    Getdents64 = 340 => SYS_GETDENTS64,
+    Prlimit64 = 341 => SYS_PRLIMIT64,
+    InotifyInit1 = 342 => SYS_INOTIFY_INIT1,
+    InotifyAddWatch = 343 => SYS_INOTIFY_ADD_WATCH,
+    InotifyRmWatch = 344 => SYS_INOTIFY_RM_WATCH,
+    Madvise = 345 => SYS_MADVISE,
+
+    // *at() syscalls for musl (dirfd-relative path operations)
+    Fchmodat = 346 => SYS_FCHMODAT,
+    Fchownat = 347 => SYS_FCHOWNAT,
+    Linkat = 348 => SYS_LINKAT,
+    Symlinkat = 349 => SYS_SYMLINKAT,
+    Readlinkat = 350 => SYS_READLINKAT,
+    MemfdCreate = 351 => SYS_MEMFD_CREATE,
+    SetTidAddress = 352 => SYS_SET_TID_ADDRESS,
+    SetRobustList = 353 => SYS_SET_ROBUST_LIST,
+    ClockNanosleep = 354 => SYS_CLOCK_NANOSLEEP,
+
+    // Credential syscalls (real/effective/saved IDs)
+    Setreuid = 355 => SYS_SETREUID,
+    Setregid = 356 => SYS_SETREGID,
+    Getresuid = 357 => SYS_GETRESUID,
+    Getresgid = 358 => SYS_GETRESGID,
+
+    // Password hashing for login/passwd
+    Crypt = 359 => SYS_CRYPT,
+
+    // Interval timers and resource usage (profiling)
+    Setitimer = 360 => SYS_SETITIMER,
+    Getitimer = 361 => SYS_GETITIMER,
+    Getrusage = 362 => SYS_GETRUSAGE,
+
+    // Cryptographic primitives for user-space protocols
+    CryptoHash = 363 => SYS_CRYPTO_HASH,
+    CryptoHmac = 364 => SYS_CRYPTO_HMAC,
+    CryptoAeadSeal = 365 => SYS_CRYPTO_AEAD_SEAL,
+    CryptoAeadOpen = 366 => SYS_CRYPTO_AEAD_OPEN,
+    CryptoX25519 = 367 => SYS_CRYPTO_X25519,
+    CryptoVerify = 368 => SYS_CRYPTO_VERIFY,
+    CryptoSign = 369 => SYS_CRYPTO_SIGN,
+
+    // In-kernel data transfer (zero-copy file serving)
+    Sendfile = 370 => SYS_SENDFILE,
+    Splice = 371 => SYS_SPLICE,
+
+    // Extended attributes (Linux ABI argument order)
+    Getxattr = 372 => SYS_GETXATTR,
+    Setxattr = 373 => SYS_SETXATTR,
+    Listxattr = 374 => SYS_LISTXATTR,
+    Removexattr = 375 => SYS_REMOVEXATTR,
+    Fgetxattr = 376 => SYS_FGETXATTR,
+    Fsetxattr = 377 => SYS_FSETXATTR,
+    Flistxattr = 378 => SYS_FLISTXATTR,
+    Fremovexattr = 379 => SYS_FREMOVEXATTR,
+
+    // Space management for sparse files
+    Fallocate = 380 => SYS_FALLOCATE,
+
+    // Process CPU times in clock ticks
+    Times = 381 => SYS_TIMES,
+
+    // Userland allocator statistics for /proc/<pid>/heap
+    HeapProfile = 382 => SYS_HEAP_PROFILE,
+
+    // Allow/deny lists checked at syscall entry
+    SyscallFilter = 383 => SYS_SYSCALL_FILTER,
+
+    // Supervisor control of IPC service namespaces
+    IpcNamespaceCreate = 384 => SYS_IPC_NAMESPACE_CREATE,
+    IpcNamespaceAssign = 385 => SYS_IPC_NAMESPACE_ASSIGN,
+    IpcNamespaceExport = 386 => SYS_IPC_NAMESPACE_EXPORT,
+
+    // Event/timer notification fds (KDE/Wayland infrastructure)
+    Getrandom = 330 => SYS_GETRANDOM,
+    EventfdCreate = 331 => SYS_EVENTFD_CREATE,
+    EventfdRead = 332 => SYS_EVENTFD_READ,
+    EventfdWrite = 333 => SYS_EVENTFD_WRITE,
+    TimerfdCreate = 334 => SYS_TIMERFD_CREATE,
+    TimerfdSettime = 335 => SYS_TIMERFD_SETTIME,
+    TimerfdGettime = 336 => SYS_TIMERFD_GETTIME,
+    SignalfdCreate = 337 => SYS_SIGNALFD_CREATE,
+    SendMsg = 338 => SYS_SENDMSG,
+    RecvMsg = 339 => SYS_RECVMSG,
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{string::String, vec::Vec};
+
+    use super::*;
+
+    /// `#define SYS_NAME value` lines of a syscall header.
+    fn header_numbers(header: &str) -> Vec<(String, usize)> {
+        header
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.strip_prefix("#define SYS_")?.split_whitespace();
+                let name = words.next()?;
+                let nr = words.next()?.parse().ok()?;
+                Some((std::format!("SYS_{}", name), nr))
+            })
+            .collect()
+    }
+
+    fn assert_matches_header(header: &str) {
+        let numbers = header_numbers(header);
+        assert!(!numbers.is_empty());
+        let define = |name: &str| -> u16 {
+            let line = header.lines().find(|l| l.starts_with(name)).unwrap();
+            line[name.len()..].trim().parse().unwrap()
+        };
+        assert_eq!(define("#define VERIDIAN_ABI_MAJOR"), ABI_VERSION.major);
+        assert_eq!(define("#define VERIDIAN_ABI_MINOR"), ABI_VERSION.minor);
+        for (name, nr) in &numbers {
+            let syscall = Syscall::try_from(*nr).unwrap_or_else(|_| panic!("{} unknown", name));
+            assert_eq!(syscall.name(), name);
+        }
+    }
+
+    #[test]
+    fn test_headers_match_table() {
+        assert_matches_header(include_str!(
+            "../../../userland/libc/include/veridian/syscall.h"
+        ));
+        assert_matches_header(include_str!(
+            "../../../toolchain/sysroot/include/veridian/syscall.h"
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &syscall in Syscall::ALL {
+            assert_eq!(Syscall::try_from(syscall.number()), Ok(syscall));
+        }
+        assert_eq!(Syscall::try_from(9), Err(()));
+        assert_eq!(Syscall::SigReturn.number(), nr::SYS_SIGRETURN);
+        assert_eq!(Syscall::ProcessGetPid.name(), "SYS_PROCESS_GETPID");
+    }
+
+    #[test]
+    fn test_version_compatibility() {
+        let kernel = AbiVersion::new(1, 5);
+        assert!(kernel.supports(AbiVersion::new(1, 0)));
+        assert!(kernel.supports(AbiVersion::new(1, 5)));
+        assert!(!kernel.supports(AbiVersion::new(1, 6)));
+        assert!(!kernel.supports(AbiVersion::new(2, 0)));
+        assert!(!kernel.supports(AbiVersion::new(0, 3)));
+        assert_eq!(AbiVersion::from_raw(kernel.to_raw()), kernel);
+        assert_eq!(ABI_VERSION.to_raw(), 0x1_0005);
+    }
+}
@@ -0,0 +1,470 @@
+//! Syscall filtering.
+//!
+//! A process installs a filter with [`crate::nr::SYS_SYSCALL_FILTER`]: a
+//! list of syscalls, a [mode](MODE_ALLOW) saying whether the list is an
+//! allowlist or a denylist, and the [action](ACTION_KILL) taken on a
+//! syscall the filter rejects. List entries are syscall numbers or
+//! [`GROUP`]` | n` for the `n`th of [`GROUPS`]. `<veridian/syscall_filter.h>`
+//! declares the same constants for C.
+
+use crate::Syscall;
+
+/// Mode: only the listed syscalls pass.
+pub const MODE_ALLOW: u32 = 0;
+/// Mode: the listed syscalls are rejected.
+pub const MODE_DENY: u32 = 1;
+/// Mode flag: the filter takes effect at the next exec, so a launcher can
+/// install it right before exec without allowing exec itself.
+pub const MODE_ON_EXEC: u32 = 0x100;
+
+/// Action: kill the process.
+pub const ACTION_KILL: u32 = 0;
+/// Action: fail the syscall with `EPERM`.
+pub const ACTION_ERRNO: u32 = 1;
+/// Action: log the syscall and let it run.
+pub const ACTION_LOG: u32 = 2;
+
+/// Marks a list entry as the index of a group in [`GROUPS`].
+pub const GROUP: u32 = 0x8000_0000;
+
+/// Most entries in one filter.
+pub const MAX_ENTRIES: usize = 512;
+
+/// A named set of syscalls.
+#[derive(Debug)]
+pub struct Group {
+    /// Name, as used after `@` in unit files.
+    pub name: &'static str,
+    /// Members.
+    pub syscalls: &'static [Syscall],
+}
+
+/// The syscall groups. Every syscall is in at least one; an index is
+/// never reused, so new groups are appended.
+pub const GROUPS: &[Group] = &[
+    // What every program needs: memory, signals, clocks, descriptors it
+    // already holds, its own identity
+    Group {
+        name: "basic",
+        syscalls: &[
+            Syscall::ProcessYield,
+            Syscall::ProcessExit,
+            Syscall::ProcessGetPid,
+            Syscall::ProcessGetPPid,
+            Syscall::ProcessGetPriority,
+            Syscall::ProcessGetcwd,
+            Syscall::ProcessUname,
+            Syscall::ProcessGetenv,
+            Syscall::ProcessUmask,
+            Syscall::ThreadExit,
+            Syscall::ThreadGetTid,
+            Syscall::MemoryMap,
+            Syscall::MemoryUnmap,
+            Syscall::MemoryProtect,
+            Syscall::MemoryBrk,
+            Syscall::Madvise,
+            Syscall::MemfdCreate,
+            Syscall::FileClose,
+            Syscall::FileRead,
+            Syscall::FileWrite,
+            Syscall::FileSeek,
+            Syscall::FileStat,
+            Syscall::FilePread,
+            Syscall::FilePwrite,
+            Syscall::Readv,
+            Syscall::Writev,
+            Syscall::FileDup,
+            Syscall::FileDup2,
+            Syscall::FileDup3,
+            Syscall::FilePipe,
+            Syscall::FilePipe2,
+            Syscall::FileFcntl,
+            Syscall::Fcntl,
+            Syscall::FileIoctl,
+            Syscall::FilePoll,
+            Syscall::FileSelect,
+            Syscall::Poll,
+            Syscall::EpollCreate,
+            Syscall::EpollCtl,
+            Syscall::EpollWait,
+            Syscall::EventfdCreate,
+            Syscall::EventfdRead,
+            Syscall::EventfdWrite,
+            Syscall::TimerfdCreate,
+            Syscall::TimerfdSettime,
+            Syscall::TimerfdGettime,
+            Syscall::SignalfdCreate,
+            Syscall::FutexWait,
+            Syscall::FutexWake,
+            Syscall::Futex,
+            Syscall::ArchPrctl,
+            Syscall::SetTidAddress,
+            Syscall::SetRobustList,
+            Syscall::SigAction,
+            Syscall::SigProcmask,
+            Syscall::SigSuspend,
+            Syscall::SigReturn,
+            Syscall::TimeGetUptime,
+            Syscall::TimeCreateTimer,
+            Syscall::TimeCancelTimer,
+            Syscall::ClockGettime,
+            Syscall::ClockGetres,
+            Syscall::ClockNanosleep,
+            Syscall::Nanosleep,
+            Syscall::Gettimeofday,
+            Syscall::Setitimer,
+            Syscall::Getitimer,
+            Syscall::Getrusage,
+            Syscall::Times,
+            Syscall::Getuid,
+            Syscall::Geteuid,
+            Syscall::Getgid,
+            Syscall::Getegid,
+            Syscall::Getresuid,
+            Syscall::Getresgid,
+            Syscall::Getgroups,
+            Syscall::Getpgid,
+            Syscall::Getpgrp,
+            Syscall::Getsid,
+            Syscall::GetPgid,
+            Syscall::GetSid,
+            Syscall::GetRlimit,
+            Syscall::Umask,
+            Syscall::Getrandom,
+            Syscall::KernelGetInfo,
+            Syscall::AbiVersion,
+            Syscall::HeapProfile,
+            Syscall::SyscallFilter,
+        ],
+    },
+    // Threads of the calling process
+    Group {
+        name: "thread",
+        syscalls: &[
+            Syscall::ThreadCreate,
+            Syscall::ThreadJoin,
+            Syscall::ThreadSetAffinity,
+            Syscall::ThreadGetAffinity,
+            Syscall::ThreadClone,
+            Syscall::Clone,
+        ],
+    },
+    // Starting, waiting for and signalling processes; sessions and limits
+    Group {
+        name: "process",
+        syscalls: &[
+            Syscall::ProcessFork,
+            Syscall::ProcessExec,
+            Syscall::ProcessWait,
+            Syscall::ProcessKill,
+            Syscall::ProcessSetPriority,
+            Syscall::Setpgid,
+            Syscall::Setsid,
+            Syscall::SetPgid,
+            Syscall::SetSid,
+            Syscall::TcSetPgrp,
+            Syscall::TcGetPgrp,
+            Syscall::SetRlimit,
+            Syscall::Prlimit64,
+        ],
+    },
+    // Changing user and group IDs
+    Group {
+        name: "credentials",
+        syscalls: &[
+            Syscall::Setuid,
+            Syscall::Setgid,
+            Syscall::Setreuid,
+            Syscall::Setregid,
+            Syscall::Setgroups,
+        ],
+    },
+    // The filesystem namespace: paths, directories, metadata
+    Group {
+        name: "file",
+        syscalls: &[
+            Syscall::FileOpen,
+            Syscall::FileOpenat,
+            Syscall::FileTruncate,
+            Syscall::FileTruncatePath,
+            Syscall::Fallocate,
+            Syscall::FileStatPath,
+            Syscall::FileLstat,
+            Syscall::FileFstatat,
+            Syscall::FileReadlink,
+            Syscall::FileAccess,
+            Syscall::FileRename,
+            Syscall::FileRenameat,
+            Syscall::FileLink,
+            Syscall::FileSymlink,
+            Syscall::FileUnlink,
+            Syscall::FileUnlinkat,
+            Syscall::FileChmod,
+            Syscall::FileFchmod,
+            Syscall::FileChown,
+            Syscall::FileFchown,
+            Syscall::DirMkdir,
+            Syscall::DirRmdir,
+            Syscall::DirOpendir,
+            Syscall::DirReaddir,
+            Syscall::DirClosedir,
+            Syscall::FileMkdirat,
+            Syscall::Getdents64,
+            Syscall::ProcessChdir,
+            Syscall::Link,
+            Syscall::Symlink,
+            Syscall::Readlink,
+            Syscall::Lstat,
+            Syscall::Fchmod,
+            Syscall::Fchown,
+            Syscall::Access,
+            Syscall::Fchmodat,
+            Syscall::Fchownat,
+            Syscall::Linkat,
+            Syscall::Symlinkat,
+            Syscall::Readlinkat,
+            Syscall::Getxattr,
+            Syscall::Setxattr,
+            Syscall::Listxattr,
+            Syscall::Removexattr,
+            Syscall::Fgetxattr,
+            Syscall::Fsetxattr,
+            Syscall::Flistxattr,
+            Syscall::Fremovexattr,
+            Syscall::InotifyInit1,
+            Syscall::InotifyAddWatch,
+            Syscall::InotifyRmWatch,
+            Syscall::Sendfile,
+            Syscall::Splice,
+            Syscall::FsSync,
+            Syscall::FsFsync,
+        ],
+    },
+    // IPC endpoints and shared memory
+    Group {
+        name: "ipc",
+        syscalls: &[
+            Syscall::IpcSend,
+            Syscall::IpcReceive,
+            Syscall::IpcCall,
+            Syscall::IpcReply,
+            Syscall::IpcCreateEndpoint,
+            Syscall::IpcBindEndpoint,
+            Syscall::IpcShareMemory,
+            Syscall::IpcMapMemory,
+            Syscall::IpcLookupEndpoint,
+            Syscall::IpcNamespaceCreate,
+            Syscall::IpcNamespaceAssign,
+            Syscall::IpcNamespaceExport,
+            Syscall::ShmOpen,
+            Syscall::ShmUnlink,
+            Syscall::ShmTruncate,
+            Syscall::ShmClose,
+        ],
+    },
+    // Sockets, local and remote
+    Group {
+        name: "net",
+        syscalls: &[
+            Syscall::SocketCreate,
+            Syscall::SocketBind,
+            Syscall::SocketListen,
+            Syscall::SocketConnect,
+            Syscall::SocketAccept,
+            Syscall::SocketSend,
+            Syscall::SocketRecv,
+            Syscall::SocketClose,
+            Syscall::SocketPair,
+            Syscall::NetSendTo,
+            Syscall::NetRecvFrom,
+            Syscall::NetGetSockName,
+            Syscall::NetGetPeerName,
+            Syscall::NetSetSockOpt,
+            Syscall::NetGetSockOpt,
+            Syscall::SendMsg,
+            Syscall::RecvMsg,
+        ],
+    },
+    // Pseudo-terminals
+    Group {
+        name: "pty",
+        syscalls: &[
+            Syscall::OpenPty,
+            Syscall::GrantPty,
+            Syscall::UnlockPty,
+            Syscall::PtsName,
+        ],
+    },
+    // Kernel cryptography and password hashing
+    Group {
+        name: "crypto",
+        syscalls: &[
+            Syscall::CryptoHash,
+            Syscall::CryptoHmac,
+            Syscall::CryptoAeadSeal,
+            Syscall::CryptoAeadOpen,
+            Syscall::CryptoX25519,
+            Syscall::CryptoVerify,
+            Syscall::CryptoSign,
+            Syscall::Crypt,
+        ],
+    },
+    // Framebuffer, input, windows, Wayland and audio
+    Group {
+        name: "graphics",
+        syscalls: &[
+            Syscall::FbGetInfo,
+            Syscall::FbMap,
+            Syscall::FbSwap,
+            Syscall::FbFlip,
+            Syscall::InputPoll,
+            Syscall::InputRead,
+            Syscall::InputInject,
+            Syscall::Screenshot,
+            Syscall::Notify,
+            Syscall::Window,
+            Syscall::WlConnect,
+            Syscall::WlDisconnect,
+            Syscall::WlSendMessage,
+            Syscall::WlRecvMessage,
+            Syscall::WlCreateShmPool,
+            Syscall::WlCreateSurface,
+            Syscall::WlCommitSurface,
+            Syscall::WlGetEvents,
+            Syscall::AudioOpen,
+            Syscall::AudioClose,
+            Syscall::AudioWrite,
+            Syscall::AudioSetVolume,
+            Syscall::AudioGetInfo,
+            Syscall::AudioStart,
+            Syscall::AudioStop,
+            Syscall::AudioPause,
+        ],
+    },
+    // Inspecting other processes and the kernel
+    Group {
+        name: "debug",
+        syscalls: &[
+            Syscall::Ptrace,
+            Syscall::Dmesg,
+            Syscall::TraceCtl,
+            Syscall::CrashDump,
+            Syscall::CapabilityInspect,
+            Syscall::AuditRead,
+        ],
+    },
+    // Package and kernel installation
+    Group {
+        name: "packages",
+        syscalls: &[
+            Syscall::PkgInstall,
+            Syscall::PkgRemove,
+            Syscall::PkgQuery,
+            Syscall::PkgList,
+            Syscall::PkgUpdate,
+            Syscall::PkgVerify,
+            Syscall::BootSlotInstall,
+            Syscall::BootSlotCommit,
+            Syscall::BootSlotQuery,
+        ],
+    },
+    Group {
+        name: "reboot",
+        syscalls: &[Syscall::Reboot],
+    },
+    Group {
+        name: "watchdog",
+        syscalls: &[Syscall::Watchdog],
+    },
+    // Everything that changes the system as a whole
+    Group {
+        name: "admin",
+        syscalls: &[
+            Syscall::CapabilityGrant,
+            Syscall::CapabilityRevoke,
+            Syscall::FsMount,
+            Syscall::FsUnmount,
+            Syscall::FileMknod,
+            Syscall::NetFirewall,
+            Syscall::NetPacketRing,
+            Syscall::PkgInstall,
+            Syscall::PkgRemove,
+            Syscall::PkgUpdate,
+            Syscall::BootSlotInstall,
+            Syscall::BootSlotCommit,
+            Syscall::Reboot,
+            Syscall::Watchdog,
+        ],
+    },
+];
+
+/// Index in [`GROUPS`] of the group called `name`.
+pub fn group(name: &str) -> Option<usize> {
+    GROUPS.iter().position(|g| g.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn test_groups_cover_every_syscall() {
+        for &syscall in Syscall::ALL {
+            assert!(
+                GROUPS.iter().any(|g| g.syscalls.contains(&syscall)),
+                "{} is in no group",
+                syscall.name()
+            );
+        }
+        assert_eq!(group("file"), Some(4));
+        assert_eq!(group("nope"), None);
+    }
+
+    #[test]
+    fn test_constants_match_header() {
+        let header = include_str!("../../../userland/libc/include/veridian/syscall_filter.h");
+        let define = |name: &str| -> u32 {
+            let line = header
+                .lines()
+                .find(|l| l.starts_with(&std::format!("#define {} ", name)))
+                .unwrap();
+            let value = line.split_whitespace().nth(2).unwrap();
+            match value.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex.trim_end_matches('u'), 16).unwrap(),
+                None => value.parse().unwrap(),
+            }
+        };
+        assert_eq!(define("FILTER_ALLOW"), MODE_ALLOW);
+        assert_eq!(define("FILTER_DENY"), MODE_DENY);
+        assert_eq!(define("FILTER_ON_EXEC"), MODE_ON_EXEC);
+        assert_eq!(define("FILTER_KILL"), ACTION_KILL);
+        assert_eq!(define("FILTER_ERRNO"), ACTION_ERRNO);
+        assert_eq!(define("FILTER_LOG"), ACTION_LOG);
+        assert_eq!(define("FILTER_GROUP"), GROUP);
+        assert_eq!(define("FILTER_MAX_ENTRIES") as usize, MAX_ENTRIES);
+
+        // `#define FILTER_GROUP_<NAME> (FILTER_GROUP | n)`, one per group
+        let mut groups = 0;
+        for line in header.lines() {
+            let Some(rest) = line.strip_prefix("#define FILTER_GROUP_") else {
+                continue;
+            };
+            let (name, value) = rest.split_once(' ').unwrap();
+            let index: usize = value
+                .split("/*")
+                .next()
+                .unwrap()
+                .trim()
+                .strip_prefix("(FILTER_GROUP | ")
+                .and_then(|v| v.strip_suffix(')'))
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert_eq!(GROUPS[index].name, name.to_lowercase());
+            groups += 1;
+        }
+        assert_eq!(groups, GROUPS.len());
+    }
+}
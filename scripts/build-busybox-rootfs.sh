@@ -246,6 +246,53 @@ phase_build() {
     echo ""
 }
 
+# =========================================================================
+# User database (/etc/passwd, /etc/group, /etc/shadow)
+# =========================================================================
+
+# Hash a password in the kernel crypt() format:
+#   $pbkdf2-sha256$<rounds>$<salt>$<hex digest>
+# The salt is derived from the user name and SOURCE_DATE_EPOCH so that
+# rootfs builds stay reproducible.
+hash_password() {
+    local user="$1" password="$2"
+    python3 - "$user" "$password" "${SOURCE_DATE_EPOCH:-0}" <<'PYEOF'
+import hashlib, sys
+user, password, epoch = sys.argv[1], sys.argv[2], sys.argv[3]
+rounds = 10000
+salt = hashlib.sha256(f"{user}:{epoch}".encode()).hexdigest()[:16]
+digest = hashlib.pbkdf2_hmac("sha256", password.encode(), salt.encode(), rounds)
+print(f"$pbkdf2-sha256${rounds}${salt}${digest.hex()}")
+PYEOF
+}
+
+# Install the default accounts into <etc-dir>. Passwords default to
+# "veridian" and can be overridden with VERIDIAN_ROOT_PASSWORD and
+# VERIDIAN_USER_PASSWORD.
+install_user_database() {
+    local etc="$1"
+    mkdir -p "$etc" "$BUILD_DIR/root" "$BUILD_DIR/home/user"
+
+    cat > "$etc/passwd" <<PWEOF
+root:x:0:0:root:/root:/bin/sh
+user:x:1000:1000:VeridianOS User:/home/user:/bin/sh
+PWEOF
+
+    cat > "$etc/group" <<GREOF
+root:x:0:
+wheel:x:10:root,user
+user:x:1000:
+GREOF
+
+    {
+        echo "root:$(hash_password root "${VERIDIAN_ROOT_PASSWORD:-veridian}"):0:0:99999:7:::"
+        echo "user:$(hash_password user "${VERIDIAN_USER_PASSWORD:-veridian}"):0:0:99999:7:::"
+    } > "$etc/shadow"
+    chmod 600 "$etc/shadow"
+
+    echo "    + /etc/{passwd,group,shadow} (root, user)"
+}
+
 # =========================================================================
 # Phase A-6: Package rootfs with BusyBox
 # =========================================================================
@@ -376,13 +423,14 @@ phase_rootfs() {
         fi
     fi
 
-    # Cross-compile init (PID 1) for user-space boot
-    local init_src="${PROJECT_ROOT}/userland/init/init.c"
-    if [ -f "$init_src" ]; then
+    # Cross-compile init (PID 1) for user-space boot, with its unit files
+    local init_dir="${PROJECT_ROOT}/userland/init"
+    if [ -f "$init_dir/init.c" ]; then
         echo -n "    init... "
-        mkdir -p "$BUILD_DIR/sbin"
+        mkdir -p "$BUILD_DIR/sbin" "$BUILD_DIR/etc/init"
+        cp "$init_dir"/units/*.toml "$BUILD_DIR/etc/init/"
         if "$CC" $pgm_cflags $pgm_ldflags -o "$BUILD_DIR/sbin/init" \
-                "${SYSROOT}/usr/lib/crt0.o" "$init_src" -lc 2>&1; then
+                "${SYSROOT}/usr/lib/crt0.o" "$init_dir/init.c" "$init_dir/unit.c" -lc 2>&1; then
             "$STRIP" "$BUILD_DIR/sbin/init" 2>/dev/null || true
             local isz
             isz=$(stat -c%s "$BUILD_DIR/sbin/init" 2>/dev/null || stat -f%z "$BUILD_DIR/sbin/init" 2>/dev/null)
@@ -392,6 +440,39 @@ phase_rootfs() {
         fi
     fi
 
+    # vctl (start/stop/status of the units supervised by init)
+    local vctl_src="${PROGRAMS_DIR}/vctl/vctl.c"
+    if [ -f "$vctl_src" ]; then
+        echo -n "    vctl... "
+        if "$CC" $pgm_cflags $pgm_ldflags -o "$BUILD_DIR/bin/vctl" \
+                "${SYSROOT}/usr/lib/crt0.o" "$vctl_src" -lc 2>&1; then
+            "$STRIP" "$BUILD_DIR/bin/vctl" 2>/dev/null || true
+            local vsz
+            vsz=$(stat -c%s "$BUILD_DIR/bin/vctl" 2>/dev/null || stat -f%z "$BUILD_DIR/bin/vctl" 2>/dev/null)
+            echo "OK ($(( vsz / 1024 )) KB)"
+        else
+            echo "FAILED"
+        fi
+    fi
+
+    # login (run by init on the console)
+    local login_src="${PROGRAMS_DIR}/login/login.c"
+    if [ -f "$login_src" ]; then
+        echo -n "    login... "
+        if "$CC" $pgm_cflags $pgm_ldflags -o "$BUILD_DIR/bin/login" \
+                "${SYSROOT}/usr/lib/crt0.o" "$login_src" -lc 2>&1; then
+            "$STRIP" "$BUILD_DIR/bin/login" 2>/dev/null || true
+            local lsz
+            lsz=$(stat -c%s "$BUILD_DIR/bin/login" 2>/dev/null || stat -f%z "$BUILD_DIR/bin/login" 2>/dev/null)
+            echo "OK ($(( lsz / 1024 )) KB)"
+        else
+            echo "FAILED"
+        fi
+    fi
+
+    # User database: root plus an unprivileged "user" account
+    install_user_database "$BUILD_DIR/etc"
+
     # Copy source files for native compilation on VeridianOS
     echo "  Adding program source for native compilation..."
     mkdir -p "$BUILD_DIR/usr/src"
@@ -442,6 +523,15 @@ phase_rootfs() {
     chmod 755 "$BUILD_DIR/usr/src/busybox_test.sh"
     echo "  + /usr/src/busybox_test.sh"
 
+    # Compile desktop input scripts for scripts/testing/desktop-smoke-test.sh
+    mkdir -p "$BUILD_DIR/usr/share/tests/ui"
+    for script in "$PROJECT_ROOT"/scripts/testing/ui/*.input; do
+        [ -f "$script" ] || continue
+        python3 "$PROJECT_ROOT/scripts/testing/mkrec.py" "$script" \
+            -o "$BUILD_DIR/usr/share/tests/ui/$(basename "$script" .input).rec" >/dev/null
+        echo "  + /usr/share/tests/ui/$(basename "$script" .input).rec"
+    done
+
     # Include native GCC toolchain if available (for Phase C)
     if [ -d "$NATIVE_GCC_DIR/usr/bin" ]; then
         echo "  Adding native GCC toolchain..."
@@ -579,7 +669,7 @@ SPECEOF
     echo ""
     echo "  Creating rootfs-busybox.tar..."
     cd "$BUILD_DIR"
-    tar cf "$ROOTFS_TAR" --format=ustar bin/ sbin/ usr/ tmp/ var/ dev/ proc/ etc/
+    tar cf "$ROOTFS_TAR" --format=ustar bin/ sbin/ usr/ tmp/ var/ dev/ proc/ etc/ root/ home/
     cd "$PROJECT_ROOT"
 
     local total_files size
@@ -0,0 +1,427 @@
+#!/usr/bin/env bash
+# VeridianOS Native Tiny C Compiler Builder
+#
+# Copyright (c) 2025-2026 VeridianOS Contributors
+# SPDX-License-Identifier: MIT OR Apache-2.0
+#
+# First step of the self-hosted toolchain: a small C compiler that runs on
+# VeridianOS.  tcc compiles, assembles and links in one static binary, so
+# together with the libc headers and archives it is enough for
+#
+#   cc hello.c && ./a.out
+#
+# inside the OS.  The build runs in two stages:
+#
+#   1. A host tcc configured with the target's paths builds libtcc1.a (tcc's
+#      runtime helpers must be compiled by tcc itself, and both sides are
+#      x86_64 ELF, so the host build produces the same objects).
+#   2. The VeridianOS cross-compiler builds a static tcc binary.
+#
+# Two packages are staged and, with --key, signed as .vpk for pkgd:
+#
+#   libc-dev   /usr/include, libc.a, libm.a, crt1.o/crti.o/crtn.o
+#   tcc        /usr/bin/tcc, /usr/bin/cc, /usr/lib/tcc/  (depends: libc-dev)
+#
+# Usage:
+#   ./scripts/build-native-tcc.sh [OPTIONS]
+#
+#   --cross-prefix PATH   Cross-toolchain prefix (default: /opt/veridian/toolchain)
+#   --output-dir PATH     Staging directory (default: target/native-tcc-staging)
+#   --key FILE            Ed25519 seed for scripts/mkvpk.py; builds .vpk files
+#   --jobs N              Parallel make jobs (default: nproc)
+#   --clean               Remove build directory before starting
+#   -h, --help            Show this help message
+
+set -euo pipefail
+
+# ---------------------------------------------------------------------------
+# Color helpers
+# ---------------------------------------------------------------------------
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+YELLOW='\033[1;33m'
+CYAN='\033[0;36m'
+BOLD='\033[1m'
+NC='\033[0m'
+
+info()    { printf "${CYAN}[INFO]${NC}  %s\n" "$*"; }
+success() { printf "${GREEN}[OK]${NC}    %s\n" "$*"; }
+warn()    { printf "${YELLOW}[WARN]${NC}  %s\n" "$*"; }
+error()   { printf "${RED}[ERROR]${NC} %s\n" "$*" >&2; }
+step()    { printf "\n${BOLD}==> %s${NC}\n" "$*"; }
+
+die() {
+    error "$@"
+    exit 1
+}
+
+# ---------------------------------------------------------------------------
+# Resolve project root
+# ---------------------------------------------------------------------------
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_ROOT="$(cd "${SCRIPT_DIR}/.." && pwd)"
+
+# ---------------------------------------------------------------------------
+# Defaults
+# ---------------------------------------------------------------------------
+ARCH="x86_64"
+CROSS_PREFIX="/opt/veridian/toolchain"
+OUTPUT_DIR=""
+SIGNING_KEY=""
+JOBS="$(nproc 2>/dev/null || sysctl -n hw.ncpu 2>/dev/null || echo 4)"
+CLEAN="no"
+
+# ---------------------------------------------------------------------------
+# tcc version and checksum
+# ---------------------------------------------------------------------------
+TCC_VERSION="0.9.27"
+TCC_URL="https://download.savannah.gnu.org/releases/tinycc/tcc-${TCC_VERSION}.tar.bz2"
+TCC_SHA256="de23af78fca90ce32dff2dd45b3432b2334740bb9bb7b05bf60fdbfc396ceb9c"
+
+# ---------------------------------------------------------------------------
+# On-target layout (baked into the tcc binary by configure)
+# ---------------------------------------------------------------------------
+TCC_DIR="/usr/lib/tcc"
+TCC_CONFIGURE_PATHS=(
+    --prefix=/usr
+    --tccdir="${TCC_DIR}"
+    --sysincludepaths="${TCC_DIR}/include:/usr/include"
+    --libpaths="${TCC_DIR}:/usr/lib"
+    --crtprefix=/usr/lib
+    # Backtraces need <sys/ucontext.h> and bounds checking needs its own
+    # libc shims; neither is required to build programs.
+    --config-backtrace=no
+    --config-bcheck=no
+)
+
+# ---------------------------------------------------------------------------
+# Build directory
+# ---------------------------------------------------------------------------
+BUILD_BASE="/tmp/veridian-native-tcc-build"
+
+# ---------------------------------------------------------------------------
+# Parse arguments
+# ---------------------------------------------------------------------------
+parse_args() {
+    while [[ $# -gt 0 ]]; do
+        case "$1" in
+            --cross-prefix)
+                CROSS_PREFIX="${2:?--cross-prefix requires a path}"
+                shift 2
+                ;;
+            --output-dir)
+                OUTPUT_DIR="${2:?--output-dir requires a path}"
+                shift 2
+                ;;
+            --key)
+                SIGNING_KEY="${2:?--key requires a file}"
+                shift 2
+                ;;
+            --jobs)
+                JOBS="${2:?--jobs requires a number}"
+                shift 2
+                ;;
+            --clean)
+                CLEAN="yes"
+                shift
+                ;;
+            -h|--help)
+                usage
+                exit 0
+                ;;
+            *)
+                die "Unknown option: $1  (try --help)"
+                ;;
+        esac
+    done
+}
+
+usage() {
+    cat <<'EOF'
+Usage: ./scripts/build-native-tcc.sh [OPTIONS]
+
+Download and cross-compile the Tiny C Compiler for VeridianOS, and stage it
+with the libc development files so `cc hello.c && ./a.out` works on-target.
+
+Options:
+  --cross-prefix PATH   Cross-toolchain prefix (default: /opt/veridian/toolchain)
+  --output-dir PATH     Staging directory (default: target/native-tcc-staging)
+  --key FILE            Ed25519 seed for scripts/mkvpk.py; builds .vpk files
+  --jobs N              Parallel make jobs (default: nproc)
+  --clean               Remove build directory before starting
+  -h, --help            Show this help message
+
+Prerequisites:
+  - Cross-toolchain for VeridianOS (scripts/build-cross-toolchain.sh)
+  - Sysroot with libc.a and crt objects (scripts/build-sysroot.sh)
+  - Host C compiler (for the stage-1 tcc that builds libtcc1.a)
+
+Example:
+  ./scripts/build-native-tcc.sh
+  ./scripts/build-native-tcc.sh --key signing.key
+  # then, on VeridianOS:  pkg install libc-dev && pkg install tcc
+EOF
+}
+
+# ---------------------------------------------------------------------------
+# Derived variables
+# ---------------------------------------------------------------------------
+setup_vars() {
+    TARGET="${ARCH}-veridian"
+
+    CROSS_CC="${CROSS_PREFIX}/bin/${TARGET}-gcc"
+    CROSS_AR="${CROSS_PREFIX}/bin/${TARGET}-ar"
+    CROSS_STRIP="${CROSS_PREFIX}/bin/${TARGET}-strip"
+
+    SYSROOT="${CROSS_PREFIX}/sysroot"
+    SRC_DIR="${BUILD_BASE}/sources/tcc-${TCC_VERSION}"
+    PKG_VERSION="$(cat "${PROJECT_ROOT}/VERSION")"
+
+    if [[ -z "${OUTPUT_DIR}" ]]; then
+        OUTPUT_DIR="${PROJECT_ROOT}/target/native-tcc-staging"
+    fi
+    TCC_STAGE="${OUTPUT_DIR}/tcc"
+    LIBC_STAGE="${OUTPUT_DIR}/libc-dev"
+}
+
+# ---------------------------------------------------------------------------
+# Verify cross-compiler
+# ---------------------------------------------------------------------------
+verify_cross_compiler() {
+    step "Verifying cross-compiler"
+
+    if [[ ! -x "${CROSS_CC}" ]]; then
+        die "Cross-compiler not found at ${CROSS_CC}.  Run scripts/build-cross-toolchain.sh first."
+    fi
+
+    for f in libc.a crt0.o crti.o crtn.o; do
+        if [[ ! -f "${SYSROOT}/usr/lib/${f}" ]]; then
+            die "Sysroot ${f} not found.  Run scripts/build-sysroot.sh first."
+        fi
+    done
+
+    command -v cc &>/dev/null || die "Host C compiler (cc) not found."
+
+    success "Cross-compiler and sysroot are present"
+}
+
+# ---------------------------------------------------------------------------
+# Download, verify and extract source
+# ---------------------------------------------------------------------------
+download_source() {
+    step "Downloading tcc ${TCC_VERSION}"
+
+    mkdir -p "${BUILD_BASE}/downloads"
+    local dest="${BUILD_BASE}/downloads/tcc-${TCC_VERSION}.tar.bz2"
+
+    if [[ -f "${dest}" ]]; then
+        info "Already downloaded"
+    else
+        info "Downloading tcc-${TCC_VERSION}.tar.bz2 ..."
+        wget -q --show-progress -O "${dest}" "${TCC_URL}" \
+            || die "Failed to download tcc"
+    fi
+
+    local got
+    got="$(sha256sum "${dest}" | awk '{print $1}')"
+    if [[ "${got}" != "${TCC_SHA256}" ]]; then
+        die "Checksum mismatch for tcc-${TCC_VERSION}.tar.bz2"
+    fi
+
+    if [[ -d "${SRC_DIR}" ]]; then
+        info "Already extracted"
+    else
+        mkdir -p "${BUILD_BASE}/sources"
+        tar -xf "${dest}" -C "${BUILD_BASE}/sources"
+    fi
+
+    success "Source ready"
+}
+
+# ---------------------------------------------------------------------------
+# Stage 1: host tcc -> libtcc1.a
+# ---------------------------------------------------------------------------
+build_libtcc1() {
+    step "Stage 1: building libtcc1.a with a host tcc"
+
+    local builddir="${BUILD_BASE}/stage1"
+    mkdir -p "${builddir}"
+
+    if [[ ! -f "${builddir}/config.mak" ]]; then
+        (
+            cd "${builddir}"
+            "${SRC_DIR}/configure" "${TCC_CONFIGURE_PATHS[@]}" --cpu="${ARCH}"
+        )
+    fi
+
+    make -C "${builddir}" -j"${JOBS}" tcc
+    make -C "${builddir}" libtcc1.a
+
+    [[ -f "${builddir}/libtcc1.a" ]] || die "libtcc1.a was not produced"
+    success "libtcc1.a built"
+}
+
+# ---------------------------------------------------------------------------
+# Stage 2: static tcc for VeridianOS
+# ---------------------------------------------------------------------------
+build_tcc() {
+    step "Stage 2: cross-compiling tcc for VeridianOS"
+
+    local builddir="${BUILD_BASE}/stage2"
+    mkdir -p "${builddir}"
+
+    if [[ ! -f "${builddir}/config.mak" ]]; then
+        (
+            cd "${builddir}"
+            # CONFIG_TCC_STATIC drops the dlopen() path: the only libc is
+            # libc.a, so tcc never loads shared objects.  `tcc -run` maps
+            # generated code writable and executable, so the binary carries
+            # PT_WXNEEDED to be exempt from the kernel's W^X policy.
+            "${SRC_DIR}/configure" "${TCC_CONFIGURE_PATHS[@]}" \
+                --cpu="${ARCH}" \
+                --cc="${CROSS_CC}" \
+                --ar="${CROSS_AR}" \
+                --extra-cflags="-O2 -DCONFIG_TCC_STATIC" \
+                --extra-ldflags="-static -Wl,-z,wxneeded"
+        )
+    fi
+
+    make -C "${builddir}" -j"${JOBS}" tcc
+
+    [[ -f "${builddir}/tcc" ]] || die "tcc binary was not produced"
+    success "tcc cross-compiled"
+}
+
+# ---------------------------------------------------------------------------
+# cc driver
+# ---------------------------------------------------------------------------
+build_cc_driver() {
+    step "Building cc driver"
+
+    mkdir -p "${BUILD_BASE}/cc"
+    "${CROSS_CC}" -std=c11 -static -O2 \
+        -o "${BUILD_BASE}/cc/cc" \
+        "${PROJECT_ROOT}/userland/programs/cc/cc.c"
+
+    success "cc driver built"
+}
+
+# ---------------------------------------------------------------------------
+# Staging
+# ---------------------------------------------------------------------------
+stage_tcc() {
+    step "Staging tcc package"
+
+    rm -rf "${TCC_STAGE}"
+    mkdir -p "${TCC_STAGE}/usr/bin" "${TCC_STAGE}${TCC_DIR}/include"
+
+    install -m 0755 "${BUILD_BASE}/stage2/tcc" "${TCC_STAGE}/usr/bin/tcc"
+    install -m 0755 "${BUILD_BASE}/cc/cc" "${TCC_STAGE}/usr/bin/cc"
+    "${CROSS_STRIP}" "${TCC_STAGE}/usr/bin/tcc" "${TCC_STAGE}/usr/bin/cc" 2>/dev/null || true
+
+    install -m 0644 "${BUILD_BASE}/stage1/libtcc1.a" "${TCC_STAGE}${TCC_DIR}/libtcc1.a"
+    cp -r "${SRC_DIR}/include/." "${TCC_STAGE}${TCC_DIR}/include/"
+
+    success "tcc staged in ${TCC_STAGE}"
+}
+
+stage_libc_dev() {
+    step "Staging libc-dev package"
+
+    rm -rf "${LIBC_STAGE}"
+    mkdir -p "${LIBC_STAGE}/usr/include" "${LIBC_STAGE}/usr/lib"
+
+    cp -r "${SYSROOT}/usr/include/." "${LIBC_STAGE}/usr/include/"
+    if [[ -f "${PROJECT_ROOT}/userland/libm/include/math.h" ]]; then
+        install -m 0644 "${PROJECT_ROOT}/userland/libm/include/math.h" \
+            "${LIBC_STAGE}/usr/include/math.h"
+    fi
+
+    for lib in libc.a libm.a crti.o crtn.o; do
+        if [[ -f "${SYSROOT}/usr/lib/${lib}" ]]; then
+            install -m 0644 "${SYSROOT}/usr/lib/${lib}" "${LIBC_STAGE}/usr/lib/${lib}"
+        fi
+    done
+    # tcc links crt1.o into executables; VeridianOS calls it crt0.o.
+    install -m 0644 "${SYSROOT}/usr/lib/crt0.o" "${LIBC_STAGE}/usr/lib/crt1.o"
+
+    success "libc-dev staged in ${LIBC_STAGE}"
+}
+
+# ---------------------------------------------------------------------------
+# Packaging
+# ---------------------------------------------------------------------------
+package_vpks() {
+    if [[ -z "${SIGNING_KEY}" ]]; then
+        warn "No --key given; skipping .vpk packaging"
+        return
+    fi
+
+    step "Packaging .vpk archives"
+
+    "${SCRIPT_DIR}/mkvpk.py" build --key "${SIGNING_KEY}" \
+        --name libc-dev --version "${PKG_VERSION}" \
+        --description "VeridianOS C library headers, archives and startup files" \
+        --license "MIT OR Apache-2.0" \
+        -o "${OUTPUT_DIR}/libc-dev.vpk" "${LIBC_STAGE}"
+
+    "${SCRIPT_DIR}/mkvpk.py" build --key "${SIGNING_KEY}" \
+        --name tcc --version "${TCC_VERSION}" \
+        --description "Tiny C Compiler -- compiler, assembler and linker" \
+        --license "LGPL-2.1-or-later" \
+        --depends libc-dev \
+        -o "${OUTPUT_DIR}/tcc.vpk" "${TCC_STAGE}"
+
+    success "Packages written to ${OUTPUT_DIR}"
+}
+
+# ---------------------------------------------------------------------------
+# Print summary
+# ---------------------------------------------------------------------------
+print_summary() {
+    step "tcc build complete"
+
+    echo ""
+    printf "${GREEN}Compiler:${NC}  %s\n" "${TCC_STAGE}/usr/bin/tcc"
+    printf "${GREEN}libc-dev:${NC}  %s\n" "${LIBC_STAGE}"
+    printf "${GREEN}Version:${NC}   tcc %s\n" "${TCC_VERSION}"
+    printf "${GREEN}Target:${NC}    %s (statically linked)\n" "${TARGET}"
+    if [[ -n "${SIGNING_KEY}" ]]; then
+        printf "${GREEN}Packages:${NC}  %s, %s\n" \
+            "${OUTPUT_DIR}/libc-dev.vpk" "${OUTPUT_DIR}/tcc.vpk"
+    fi
+    echo ""
+}
+
+# ===========================================================================
+# Main
+# ===========================================================================
+main() {
+    parse_args "$@"
+
+    echo ""
+    printf "${BOLD}VeridianOS Native Tiny C Compiler Builder${NC}\n"
+    printf "  Cross-prefix:  %s\n" "${CROSS_PREFIX}"
+    printf "  Jobs:          %s\n" "${JOBS}"
+    printf "  Clean:         %s\n" "${CLEAN}"
+    echo ""
+
+    setup_vars
+
+    if [[ "${CLEAN}" == "yes" ]]; then
+        info "Cleaning build directory: ${BUILD_BASE}"
+        rm -rf "${BUILD_BASE}"
+    fi
+
+    verify_cross_compiler
+    download_source
+    build_libtcc1
+    build_tcc
+    build_cc_driver
+    stage_tcc
+    stage_libc_dev
+    package_vpks
+    print_summary
+}
+
+main "$@"
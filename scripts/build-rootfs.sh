@@ -1,7 +1,7 @@
 #!/bin/bash
 # build-rootfs.sh — Cross-compile user-space programs and package into rootfs.tar
 #
-# Usage: ./scripts/build-rootfs.sh [toolchain-prefix]
+# Usage: [ARCH=aarch64|riscv64] ./scripts/build-rootfs.sh [toolchain-prefix]
 #
 # Creates a TAR archive containing /bin/<programs> for loading into
 # VeridianOS via the virtio-blk TAR loader at boot time.
@@ -15,13 +15,15 @@ set -euo pipefail
 SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
 PROJECT_ROOT="$(dirname "$SCRIPT_DIR")"
 TOOLCHAIN_PREFIX="${1:-$HOME/veridian-toolchain}"
+ARCH="${ARCH:-x86_64}"
 
-CC="${TOOLCHAIN_PREFIX}/bin/x86_64-veridian-gcc"
-STRIP="${TOOLCHAIN_PREFIX}/bin/x86_64-veridian-strip"
+CC="${TOOLCHAIN_PREFIX}/bin/${ARCH}-veridian-gcc"
+STRIP="${TOOLCHAIN_PREFIX}/bin/${ARCH}-veridian-strip"
 SYSROOT="${TOOLCHAIN_PREFIX}/sysroot"
 
 TESTS_DIR="${PROJECT_ROOT}/userland/tests"
 PROGRAMS_DIR="${PROJECT_ROOT}/userland/programs"
+COREUTILS_DIR="${PROJECT_ROOT}/userland/coreutils"
 LIBC_DIR="${PROJECT_ROOT}/userland/libc"
 BUILD_DIR="${PROJECT_ROOT}/target/rootfs-build"
 ROOTFS_TAR="${PROJECT_ROOT}/target/rootfs.tar"
@@ -34,6 +36,7 @@ if [ ! -x "$CC" ]; then
 fi
 
 echo "=== VeridianOS rootfs builder ==="
+echo "Arch:      $ARCH"
 echo "Compiler:  $CC"
 echo "Sysroot:   $SYSROOT"
 echo ""
@@ -46,6 +49,13 @@ mkdir -p "$BUILD_DIR/bin"
 # Common compiler flags
 # =========================================================================
 
+case "$ARCH" in
+    x86_64)  ARCH_FLAGS="-mno-red-zone -mcmodel=small" ;;
+    aarch64) ARCH_FLAGS="-mgeneral-regs-only" ;;
+    riscv64) ARCH_FLAGS="-march=rv64gc -mabi=lp64d" ;;
+    *)       echo "ERROR: unsupported ARCH=$ARCH"; exit 1 ;;
+esac
+
 LIBC_INCDIR="${LIBC_DIR}/include"
 SYS_INCDIR="${SYSROOT}/usr/include"
 LIBC_LIBDIR="${SYSROOT}/usr/lib"
@@ -55,14 +65,14 @@ CRT0="${SYSROOT}/usr/lib/crt0.o"
 CFLAGS_LIBC="-std=c11 -static -O2"
 CFLAGS_LIBC+=" -nostdinc -isystem ${LIBC_INCDIR} -isystem ${SYS_INCDIR}"
 CFLAGS_LIBC+=" -fno-stack-protector -ffreestanding"
-CFLAGS_LIBC+=" -mno-red-zone -mcmodel=small"
+CFLAGS_LIBC+=" ${ARCH_FLAGS}"
 CFLAGS_LIBC+=" -Wall -Wextra -Wno-unused-parameter"
 
 LDFLAGS_LIBC="-static -nostdlib -L${LIBC_LIBDIR}"
 
 # Flags for no-libc programs
 CFLAGS_MINIMAL="-nostdlib -nostdinc -ffreestanding -static -O2"
-CFLAGS_MINIMAL+=" -mno-red-zone -mcmodel=small -Wall -Wextra"
+CFLAGS_MINIMAL+=" ${ARCH_FLAGS} -Wall -Wextra"
 
 BUILT_COUNT=0
 
@@ -71,12 +81,12 @@ BUILT_COUNT=0
 # =========================================================================
 compile_libc_program() {
     local name="$1"
-    local src="$2"
+    local src="$2"              # Source file(s), space-separated
     local extra_libs="${3:-}"   # Optional extra libraries (e.g. "-lcurses")
     local out="$BUILD_DIR/bin/$name"
 
     echo -n "  Compiling $name... "
-    if "$CC" $CFLAGS_LIBC $LDFLAGS_LIBC -o "$out" "$CRT0" "$src" $extra_libs -lc 2>&1; then
+    if "$CC" $CFLAGS_LIBC $LDFLAGS_LIBC -o "$out" "$CRT0" $src $extra_libs -lc 2>&1; then
         "$STRIP" "$out" 2>/dev/null || true
         local size
         size=$(stat -c%s "$out" 2>/dev/null || stat -f%z "$out" 2>/dev/null)
@@ -102,11 +112,91 @@ if [ -f "${PROGRAMS_DIR}/sysinfo/sysinfo.c" ]; then
     compile_libc_program "sysinfo" "${PROGRAMS_DIR}/sysinfo/sysinfo.c"
 fi
 
+# login (console login, spawned by init)
+if [ -f "${PROGRAMS_DIR}/login/login.c" ]; then
+    compile_libc_program "login" "${PROGRAMS_DIR}/login/login.c"
+fi
+
+# pkgd (package service, spawned by init; backs the kernel `pkg` builtin)
+if [ -f "${PROGRAMS_DIR}/pkgd/main.c" ]; then
+    PKGD_DIR="${PROGRAMS_DIR}/pkgd"
+    compile_libc_program "pkgd" "${PKGD_DIR}/main.c ${PKGD_DIR}/vpk.c ${PKGD_DIR}/fetch.c ${PKGD_DIR}/db.c ${PKGD_DIR}/txn.c"
+fi
+
+# vsshd (remote shell daemon, spawned by init)
+if [ -f "${PROGRAMS_DIR}/vsshd/main.c" ]; then
+    VSSHD_DIR="${PROGRAMS_DIR}/vsshd"
+    compile_libc_program "vsshd" "${VSSHD_DIR}/main.c ${VSSHD_DIR}/transport.c ${VSSHD_DIR}/auth.c ${VSSHD_DIR}/session.c"
+fi
+
+# ktrace (kernel tracepoint control, Chrome trace JSON export)
+if [ -f "${PROGRAMS_DIR}/ktrace/ktrace.c" ]; then
+    compile_libc_program "ktrace" "${PROGRAMS_DIR}/ktrace/ktrace.c"
+fi
+
+# vscreenshot (PNG screenshots and frame-sequence screen recording)
+if [ -f "${PROGRAMS_DIR}/vscreenshot/vscreenshot.c" ]; then
+    compile_libc_program "vscreenshot" "${PROGRAMS_DIR}/vscreenshot/vscreenshot.c"
+fi
+
+# notify-send (desktop notifications from scripts and services)
+if [ -f "${PROGRAMS_DIR}/notify-send/notify-send.c" ]; then
+    compile_libc_program "notify-send" "${PROGRAMS_DIR}/notify-send/notify-send.c"
+fi
+
+# sysmon (desktop process monitor: CPU/memory graphs, kill and renice)
+if [ -f "${PROGRAMS_DIR}/sysmon/sysmon.c" ]; then
+    compile_libc_program "sysmon" "${PROGRAMS_DIR}/sysmon/sysmon.c"
+fi
+
+# osk (on-screen keyboard: injects keys into the focused window)
+if [ -f "${PROGRAMS_DIR}/osk/osk.c" ]; then
+    compile_libc_program "osk" "${PROGRAMS_DIR}/osk/osk.c"
+fi
+
+# ip (interface link state, MTU and IPv4 addresses)
+if [ -f "${PROGRAMS_DIR}/ip/ip.c" ]; then
+    compile_libc_program "ip" "${PROGRAMS_DIR}/ip/ip.c"
+fi
+
+# vfw (packet filter rules and connection tracking)
+if [ -f "${PROGRAMS_DIR}/vfw/vfw.c" ]; then
+    compile_libc_program "vfw" "${PROGRAMS_DIR}/vfw/vfw.c"
+fi
+
+# crashdump (previous boot's kernel crash dump)
+if [ -f "${PROGRAMS_DIR}/crashdump/crashdump.c" ]; then
+    compile_libc_program "crashdump" "${PROGRAMS_DIR}/crashdump/crashdump.c"
+fi
+
+# watchdogd (pets the kernel watchdog while health checks pass, spawned by init)
+if [ -f "${PROGRAMS_DIR}/watchdogd/watchdogd.c" ]; then
+    compile_libc_program "watchdogd" "${PROGRAMS_DIR}/watchdogd/watchdogd.c"
+fi
+
+# vctl (start/stop/status of the units supervised by init)
+if [ -f "${PROGRAMS_DIR}/vctl/vctl.c" ]; then
+    compile_libc_program "vctl" "${PROGRAMS_DIR}/vctl/vctl.c"
+fi
+
 # edit (nano-inspired text editor)
 if [ -f "${PROGRAMS_DIR}/edit/edit.c" ]; then
     compile_libc_program "edit" "${PROGRAMS_DIR}/edit/edit.c" "-lcurses"
 fi
 
+# =========================================================================
+# 1b. Compile coreutils (one /bin binary per source file)
+# =========================================================================
+echo "--- Coreutils ---"
+
+for src in "$COREUTILS_DIR"/*.c; do
+    [ -f "$src" ] || continue
+    name="$(basename "$src" .c)"
+    compile_libc_program "$name" "$src"
+done
+
+echo ""
+
 # =========================================================================
 # 2. Compile test programs from tests/
 # =========================================================================
@@ -117,7 +207,8 @@ for src in "$TESTS_DIR"/*.c; do
     name="$(basename "$src" .c)"
     out="$BUILD_DIR/bin/$name"
 
-    if [ "$name" = "minimal" ] || [ "$name" = "fork_test" ] || [ "$name" = "exec_test" ]; then
+    if [ "$name" = "minimal" ] || [ "$name" = "fork_test" ] || [ "$name" = "exec_test" ] \
+        || [ "$name" = "syscall_abi_test" ]; then
         # These provide their own _start -- no libc
         echo -n "  Compiling $name... "
         if "$CC" $CFLAGS_MINIMAL -o "$out" "$src" 2>&1; then
@@ -144,7 +235,7 @@ fi
 # 3. Validate all binaries are statically linked (no PT_INTERP)
 # =========================================================================
 echo "--- Static linking validation ---"
-READELF="${TOOLCHAIN_PREFIX}/bin/x86_64-veridian-readelf"
+READELF="${TOOLCHAIN_PREFIX}/bin/${ARCH}-veridian-readelf"
 if [ ! -x "$READELF" ]; then
     # Fall back to host readelf
     READELF="readelf"
@@ -174,10 +265,39 @@ echo ""
 # 4. Create the TAR archive
 # =========================================================================
 
+# Ship the kernel SBOM (written by build-kernel.sh) as /etc/veridian-release
+KERNEL_SBOM="${PROJECT_ROOT}/target/x86_64-veridian/release/veridian-release"
+[ -f "$KERNEL_SBOM" ] || KERNEL_SBOM="${PROJECT_ROOT}/target/x86_64-veridian/debug/veridian-release"
+ROOTFS_DIRS="bin/ etc/ usr/"
+mkdir -p "$BUILD_DIR/etc/init"
+if [ -f "$KERNEL_SBOM" ]; then
+    cp "$KERNEL_SBOM" "$BUILD_DIR/etc/veridian-release"
+fi
+
+# Service unit files read by init and the kernel's `service` builtin
+cp "${PROJECT_ROOT}/userland/init/units/"*.toml "$BUILD_DIR/etc/init/"
+
+# Message catalogs, installed as .po sources (the loaders parse both .po and
+# .mo, so no msgfmt step is needed)
+for po in "${PROJECT_ROOT}/userland/locale/"*/*.po; do
+    lang=$(basename "$(dirname "$po")")
+    mkdir -p "$BUILD_DIR/usr/share/locale/$lang/LC_MESSAGES"
+    cp "$po" "$BUILD_DIR/usr/share/locale/$lang/LC_MESSAGES/"
+done
+
+# Reproducible archive: sorted entries, fixed mtime/owner (SOURCE_DATE_EPOCH
+# defaults to the last commit time)
+if [ -z "${SOURCE_DATE_EPOCH:-}" ]; then
+    SOURCE_DATE_EPOCH=$(git -C "$PROJECT_ROOT" log -1 --format=%ct 2>/dev/null || echo 0)
+fi
+
 echo "Creating rootfs.tar with $BUILT_COUNT programs..."
 cd "$BUILD_DIR"
-tar cf "$ROOTFS_TAR" bin/
+# shellcheck disable=SC2086
+tar --sort=name --mtime="@${SOURCE_DATE_EPOCH}" --owner=0 --group=0 --numeric-owner \
+    --format=ustar -cf "$ROOTFS_TAR" $ROOTFS_DIRS
 cd "$PROJECT_ROOT"
+sha256sum "$ROOTFS_TAR" | sed "s|${PROJECT_ROOT}/||" > "${ROOTFS_TAR}.sha256"
 
 # Also copy to project root for convenience
 cp "$ROOTFS_TAR" "${PROJECT_ROOT}/rootfs.tar"
@@ -145,7 +145,8 @@ for src in "$TESTS_DIR"/*.c; do
     # Skip selfhost_test -- it's a source file for on-OS compilation
     [ "$name" = "selfhost_test" ] && continue
 
-    if [ "$name" = "minimal" ] || [ "$name" = "fork_test" ] || [ "$name" = "exec_test" ]; then
+    if [ "$name" = "minimal" ] || [ "$name" = "fork_test" ] || [ "$name" = "exec_test" ] \
+        || [ "$name" = "syscall_abi_test" ]; then
         echo -n "  Compiling $name... "
         if "$CC" $CFLAGS_MINIMAL -o "$out" "$src" 2>&1; then
             "$STRIP" "$out" 2>/dev/null || true
@@ -0,0 +1,231 @@
+#!/usr/bin/env python3
+"""
+Build and sign a .vpk package for the VeridianOS pkgd service.
+
+The archive layout matches kernel/src/pkg/vpk.rs: a 48-byte header, a TOML
+manifest, an LZ4-framed (or uncompressed) file table and an Ed25519
+signature over everything before it.
+
+    # one-time: create a signing key and trust it on the target
+    scripts/mkvpk.py keygen signing.key >> rootfs/etc/pkg/trusted.keys
+
+    # package everything under ./staging as absolute paths
+    scripts/mkvpk.py build --key signing.key --name hello --version 1.0.0 \\
+        --depends libgreet -o hello.vpk ./staging
+
+keygen/pubkey print "<hex public key> developer", the line format of
+/etc/pkg/trusted.keys. Ed25519 is implemented here (RFC 8032) so the tool
+needs nothing beyond the Python standard library.
+"""
+
+import argparse
+import hashlib
+import os
+import stat
+import struct
+import sys
+from pathlib import Path
+
+VPK_MAGIC = b"VPK1"
+VPK_VERSION = 1
+COMPRESSION_NONE = 0
+COMPRESSION_LZ4 = 2
+LZ4_MAGIC = 0x184D2204
+LZ4_BLOCK_SIZE = 64 * 1024
+
+# ---------------------------------------------------------------------------
+# Ed25519 (RFC 8032, section 5.1)
+# ---------------------------------------------------------------------------
+
+P = 2**255 - 19
+L = 2**252 + 27742317777372353535851937790883648493
+D = -121665 * pow(121666, P - 2, P) % P
+SQRT_M1 = pow(2, (P - 1) // 4, P)
+
+
+def _recover_x(y, sign):
+    x2 = (y * y - 1) * pow(D * y * y + 1, P - 2, P)
+    x = pow(x2, (P + 3) // 8, P)
+    if (x * x - x2) % P != 0:
+        x = x * SQRT_M1 % P
+    if x & 1 != sign:
+        x = P - x
+    return x
+
+
+_GY = 4 * pow(5, P - 2, P) % P
+_GX = _recover_x(_GY, 0)
+G = (_GX, _GY, 1, _GX * _GY % P)
+
+
+def _add(a, b):
+    x1, y1, z1, t1 = a
+    x2, y2, z2, t2 = b
+    aa = (y1 - x1) * (y2 - x2) % P
+    bb = (y1 + x1) * (y2 + x2) % P
+    cc = 2 * t1 * t2 * D % P
+    dd = 2 * z1 * z2 % P
+    e, f, g, h = bb - aa, dd - cc, dd + cc, bb + aa
+    return (e * f % P, g * h % P, f * g % P, e * h % P)
+
+
+def _mul(s, point):
+    q = (0, 1, 1, 0)
+    while s:
+        if s & 1:
+            q = _add(q, point)
+        point = _add(point, point)
+        s >>= 1
+    return q
+
+
+def _encode(point):
+    x, y, z, _ = point
+    zi = pow(z, P - 2, P)
+    x, y = x * zi % P, y * zi % P
+    return (y | ((x & 1) << 255)).to_bytes(32, "little")
+
+
+def _sha512_int(*parts):
+    return int.from_bytes(hashlib.sha512(b"".join(parts)).digest(), "little")
+
+
+def _expand(seed):
+    h = hashlib.sha512(seed).digest()
+    a = int.from_bytes(h[:32], "little")
+    a &= (1 << 254) - 8
+    a |= 1 << 254
+    return a, h[32:]
+
+
+def public_key(seed):
+    a, _ = _expand(seed)
+    return _encode(_mul(a, G))
+
+
+def sign(seed, message):
+    a, prefix = _expand(seed)
+    pub = _encode(_mul(a, G))
+    r = _sha512_int(prefix, message) % L
+    big_r = _encode(_mul(r, G))
+    k = _sha512_int(big_r, pub, message) % L
+    s = (r + k * a) % L
+    return big_r + s.to_bytes(32, "little")
+
+
+# ---------------------------------------------------------------------------
+# Archive
+# ---------------------------------------------------------------------------
+
+
+def lz4_frame(data):
+    """LZ4 frame as read by the kernel and pkgd, using stored blocks."""
+    header = struct.pack("<IBBQ", LZ4_MAGIC, 0x64, 0x40, len(data))
+    out = bytearray(header)
+    out.append((sum(header[4:12]) & 0xFF) >> 1)
+    for pos in range(0, len(data), LZ4_BLOCK_SIZE):
+        block = data[pos:pos + LZ4_BLOCK_SIZE]
+        out += struct.pack("<I", len(block) | 0x80000000) + block
+    out += struct.pack("<I", 0)
+    return bytes(out)
+
+
+def file_table(root):
+    entries = []
+    for path in sorted(p for p in Path(root).rglob("*") if p.is_file()):
+        rel = "/" + path.relative_to(root).as_posix()
+        mode = stat.S_IMODE(path.stat().st_mode)
+        entries.append((rel.encode(), mode, path.read_bytes()))
+
+    out = bytearray(struct.pack("<I", len(entries)))
+    for rel, mode, data in entries:
+        out += struct.pack("<H", len(rel)) + rel
+        out += struct.pack("<QI", len(data), mode) + data
+    return bytes(out), len(entries)
+
+
+def toml_string(value):
+    return '"' + value.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+
+def manifest(args):
+    depends = [d for d in (args.depends or "").split(",") if d]
+    return (
+        "[package]\n"
+        f"name = {toml_string(args.name)}\n"
+        f"version = {toml_string(args.version)}\n"
+        f"description = {toml_string(args.description)}\n"
+        f"license = {toml_string(args.license)}\n"
+        f"depends = [{', '.join(toml_string(d) for d in depends)}]\n"
+    ).encode()
+
+
+def read_seed(path):
+    seed = Path(path).read_bytes()
+    if len(seed) != 32:
+        sys.exit(f"{path}: expected a 32-byte Ed25519 seed")
+    return seed
+
+
+def cmd_keygen(args):
+    if os.path.exists(args.key):
+        sys.exit(f"{args.key} already exists")
+    seed = os.urandom(32)
+    fd = os.open(args.key, os.O_WRONLY | os.O_CREAT | os.O_EXCL, 0o600)
+    with os.fdopen(fd, "wb") as f:
+        f.write(seed)
+    print(f"{public_key(seed).hex()} developer")
+
+
+def cmd_pubkey(args):
+    print(f"{public_key(read_seed(args.key)).hex()} developer")
+
+
+def cmd_build(args):
+    seed = read_seed(args.key)
+    toml = manifest(args)
+    table, count = file_table(args.root)
+    if args.no_compress:
+        compression, archive = COMPRESSION_NONE, table
+    else:
+        compression, archive = COMPRESSION_LZ4, lz4_frame(table)
+
+    body = struct.pack("<4sHBBII", VPK_MAGIC, VPK_VERSION, compression, 0,
+                       len(toml), len(archive))
+    body += public_key(seed) + toml + archive
+    Path(args.output).write_bytes(body + sign(seed, body))
+    print(f"{args.output}: {args.name} {args.version}, {count} files")
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[1])
+    sub = parser.add_subparsers(dest="command", required=True)
+
+    p = sub.add_parser("keygen", help="create a signing key")
+    p.add_argument("key")
+    p.set_defaults(func=cmd_keygen)
+
+    p = sub.add_parser("pubkey", help="print the public key of a signing key")
+    p.add_argument("key")
+    p.set_defaults(func=cmd_pubkey)
+
+    p = sub.add_parser("build", help="build and sign a package")
+    p.add_argument("--key", required=True, help="32-byte Ed25519 seed file")
+    p.add_argument("--name", required=True)
+    p.add_argument("--version", required=True)
+    p.add_argument("--description", default="")
+    p.add_argument("--license", default="")
+    p.add_argument("--depends", help="comma-separated package names")
+    p.add_argument("--no-compress", action="store_true",
+                   help="store the file table uncompressed")
+    p.add_argument("-o", "--output", required=True)
+    p.add_argument("root", help="staging directory mirroring the install root")
+    p.set_defaults(func=cmd_build)
+
+    args = parser.parse_args()
+    args.func(args)
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -221,6 +221,9 @@ run_test() {
                     -no-reboot
                 )
             fi
+            # Hardware watchdog: a hung kernel resets the machine, which
+            # -no-reboot turns into a QEMU exit instead of a stalled run
+            qemu_cmd+=(-device i6300esb)
             # ISA debug exit for x86_64 (port 0xf4)
             qemu_exit_args=(-device isa-debug-exit,iobase=0xf4,iosize=0x04)
             ;;
@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+"""
+Check a QEMU screendump (binary PPM) taken during a desktop test.
+
+The desktop shows a clock and animations, so screenshots are never
+pixel-identical between runs. The checks are therefore coarse but
+deterministic: the frame has the expected size, is not blank, and (for a
+step that should change the screen) differs enough from the previous one.
+
+    scripts/testing/check-screenshot.py shot.ppm --min-colors 16 \\
+        --differs-from before.ppm --min-changed 0.02 --png shot.png
+
+Exits 0 if every check passes and 1 otherwise, printing one line per
+failed check. --png converts the image for CI artifact viewers either way.
+"""
+
+import argparse
+import struct
+import sys
+import zlib
+
+# Compare every Nth pixel; plenty for whole-screen changes and much faster.
+SAMPLE_STEP = 7
+
+
+def load_ppm(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    fields = []
+    pos = 0
+    while len(fields) < 4:
+        while data[pos:pos + 1].isspace():
+            pos += 1
+        if data[pos:pos + 1] == b"#":
+            pos = data.index(b"\n", pos)
+            continue
+        end = pos
+        while not data[end:end + 1].isspace():
+            end += 1
+        fields.append(data[pos:end])
+        pos = end
+    if fields[0] != b"P6" or int(fields[3]) != 255:
+        raise ValueError(f"{path}: not an 8-bit binary PPM")
+    width, height = int(fields[1]), int(fields[2])
+    pixels = data[pos + 1:pos + 1 + width * height * 3]
+    if len(pixels) != width * height * 3:
+        raise ValueError(f"{path}: truncated")
+    return width, height, pixels
+
+
+def sampled(pixels):
+    return [pixels[i:i + 3] for i in range(0, len(pixels), 3 * SAMPLE_STEP)]
+
+
+def write_png(path, width, height, pixels):
+    def chunk(kind, body):
+        crc = zlib.crc32(kind + body) & 0xFFFFFFFF
+        return struct.pack(">I", len(body)) + kind + body + struct.pack(">I", crc)
+
+    stride = width * 3
+    raw = b"".join(b"\0" + pixels[y * stride:(y + 1) * stride] for y in range(height))
+    with open(path, "wb") as f:
+        f.write(b"\x89PNG\r\n\x1a\n")
+        f.write(chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 2, 0, 0, 0)))
+        f.write(chunk(b"IDAT", zlib.compress(raw, 6)))
+        f.write(chunk(b"IEND", b""))
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.split("\n\n")[0].strip())
+    parser.add_argument("image", help="screendump to check (PPM)")
+    parser.add_argument("--size", help="expected WIDTHxHEIGHT")
+    parser.add_argument("--min-colors", type=int, default=0,
+                        help="fail if fewer distinct colours are sampled")
+    parser.add_argument("--differs-from", metavar="PPM",
+                        help="earlier screendump this one must differ from")
+    parser.add_argument("--min-changed", type=float, default=0.01,
+                        help="fraction of sampled pixels that must change")
+    parser.add_argument("--png", metavar="OUT", help="also write the image as PNG")
+    args = parser.parse_args()
+
+    try:
+        width, height, pixels = load_ppm(args.image)
+    except (OSError, ValueError) as e:
+        print(f"FAIL: {e}")
+        return 1
+    if args.png:
+        write_png(args.png, width, height, pixels)
+
+    failures = []
+    if args.size and args.size != f"{width}x{height}":
+        failures.append(f"size is {width}x{height}, expected {args.size}")
+
+    samples = sampled(pixels)
+    colors = len(set(samples))
+    if colors < args.min_colors:
+        failures.append(f"only {colors} distinct colours (blank screen?)")
+
+    if args.differs_from:
+        try:
+            pw, ph, previous = load_ppm(args.differs_from)
+        except (OSError, ValueError) as e:
+            failures.append(str(e))
+        else:
+            if (pw, ph) != (width, height):
+                changed = 1.0
+            else:
+                before = sampled(previous)
+                changed = sum(a != b for a, b in zip(samples, before)) / len(samples)
+            if changed < args.min_changed:
+                failures.append(f"only {changed:.1%} of the screen changed since "
+                                f"{args.differs_from}")
+
+    for failure in failures:
+        print(f"FAIL: {args.image}: {failure}")
+    if not failures:
+        print(f"ok: {args.image}: {width}x{height}, {colors} colours")
+    return 1 if failures else 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
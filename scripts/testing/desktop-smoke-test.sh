@@ -0,0 +1,243 @@
+#!/usr/bin/env bash
+# desktop-smoke-test.sh -- End-to-end desktop bring-up smoke test (x86_64)
+#
+# Boots the full UEFI image headless on a virtio-gpu display and drives the
+# desktop with a scripted input recording (scripts/testing/ui/
+# desktop-smoke.input, installed in the rootfs as
+# /usr/share/tests/ui/desktop-smoke.rec by build-busybox-rootfs.sh):
+#
+#   boot      kernel prints BOOTOK
+#   shell     kernel shell prompt on the serial console
+#   desktop   `startgui` enters the compositor render loop
+#   lock      Ctrl+Alt+L locks the session
+#   login     root logs back in on the lock screen
+#   terminal  a command typed into the terminal window runs
+#   window    the launcher opens a new window
+#   replay    the replay finishes with every checkpoint captured
+#
+# Each milestone waits for its serial marker. GUI milestones also take a
+# QEMU screendump, which must not be blank and must differ from the step
+# before. On failure the serial log and a final screenshot are kept in the
+# artifacts directory for the CI job to upload.
+#
+# Usage:
+#   ./scripts/testing/desktop-smoke-test.sh [--timeout SECS] [--release]
+#       [--rootfs IMG] [--artifacts DIR] [--refs GUEST_DIR]
+#
+#   --timeout SECS   per-milestone timeout (default 180)
+#   --artifacts DIR  where logs and screenshots go (default target/desktop-smoke)
+#   --refs DIR       guest directory of reference checkpoint-N.bmp files;
+#                    the replay then fails on any pixel mismatch
+#
+# Exit status: 0 if every milestone passed, 1 if one failed or timed out,
+# 2 on a setup error.
+
+set -euo pipefail
+
+PROJECT_ROOT="$(cd "$(dirname "$0")/../.." && pwd)"
+TESTING_DIR="${PROJECT_ROOT}/scripts/testing"
+
+TIMEOUT=180
+BUILD_MODE="debug"
+ROOTFS="${PROJECT_ROOT}/target/rootfs-blockfs.img"
+ARTIFACTS="${PROJECT_ROOT}/target/desktop-smoke"
+REFS=""
+RECORDING="/usr/share/tests/ui/desktop-smoke.rec"
+
+while [[ $# -gt 0 ]]; do
+    case "$1" in
+        --timeout)   TIMEOUT="$2"; shift 2 ;;
+        --release)   BUILD_MODE="release"; shift ;;
+        --rootfs)    ROOTFS="$2"; shift 2 ;;
+        --artifacts) ARTIFACTS="$2"; shift 2 ;;
+        --refs)      REFS="$2"; shift 2 ;;
+        --help|-h)
+            sed -n '2,34p' "$0" | sed 's/^# \{0,1\}//'
+            exit 0
+            ;;
+        *)
+            echo "Error: unknown option '$1'"
+            exit 2
+            ;;
+    esac
+done
+
+OVMF=""
+for candidate in \
+    /usr/share/edk2/x64/OVMF.4m.fd \
+    /usr/share/OVMF/OVMF_CODE.fd \
+    /usr/share/edk2/ovmf/OVMF_CODE.fd; do
+    if [[ -f "$candidate" ]]; then
+        OVMF="$candidate"
+        break
+    fi
+done
+if [[ -z "$OVMF" ]]; then
+    echo "Error: OVMF firmware not found (see scripts/run-veridian.sh)"
+    exit 2
+fi
+
+UEFI_IMG="${PROJECT_ROOT}/target/x86_64-veridian/${BUILD_MODE}/veridian-uefi.img"
+for file in "$UEFI_IMG" "$ROOTFS"; do
+    if [[ ! -f "$file" ]]; then
+        echo "Error: required file not found: $file"
+        exit 2
+    fi
+done
+
+rm -rf "$ARTIFACTS"
+mkdir -p "$ARTIFACTS"
+LOG="${ARTIFACTS}/serial.log"
+MONITOR="${ARTIFACTS}/monitor.sock"
+FIFO="$(mktemp -u -t veridian-smoke.XXXXXX.in)"
+mkfifo "$FIFO"
+QEMU_PID=""
+trap 'rm -f "$FIFO" "$MONITOR"; [[ -n "$QEMU_PID" ]] && kill "$QEMU_PID" 2>/dev/null || true' EXIT
+
+# A fixed RTC base keeps the panel clock (and so the screenshots) the same
+# from run to run.
+qemu-system-x86_64 \
+    -machine accel=kvm:tcg \
+    -smp 1 -m 2048M \
+    -rtc base=2026-01-01T00:00:00,clock=vm \
+    -drive "if=pflash,format=raw,readonly=on,file=${OVMF}" \
+    -drive "id=disk0,if=none,format=raw,file=${UEFI_IMG}" \
+    -device ide-hd,drive=disk0 \
+    -drive "file=${ROOTFS},if=none,id=vd0,format=raw,snapshot=on" \
+    -device virtio-blk-pci,drive=vd0 \
+    -vga none -device virtio-gpu-pci -display none \
+    -monitor "unix:${MONITOR},server,nowait" \
+    -serial stdio <"$FIFO" >"$LOG" 2>&1 &
+QEMU_PID=$!
+exec 3>"$FIFO"
+
+# =========================================================================
+# Helpers
+# =========================================================================
+
+START=$SECONDS
+OFFSET=0        # log byte offset the next milestone is searched from
+STEP=0
+PREV_SHOT=""
+
+# Run one HMP command on the QEMU monitor.
+monitor() {
+    python3 - "$MONITOR" "$1" <<'EOF'
+import socket, sys
+s = socket.socket(socket.AF_UNIX)
+s.settimeout(30)
+s.connect(sys.argv[1])
+def prompt():
+    buf = b""
+    while not buf.endswith(b"(qemu) "):
+        data = s.recv(4096)
+        if not data:
+            break
+        buf += data
+prompt()
+s.sendall(sys.argv[2].encode() + b"\n")
+prompt()
+EOF
+}
+
+screendump() {
+    local file="$1"
+    monitor "screendump ${file}" >/dev/null 2>&1 && [[ -s "$file" ]]
+}
+
+fail() {
+    echo "FAIL: $*"
+    echo "FAIL: $*" >>"${ARTIFACTS}/milestones.txt"
+    if screendump "${ARTIFACTS}/failure.ppm"; then
+        python3 "${TESTING_DIR}/check-screenshot.py" "${ARTIFACTS}/failure.ppm" \
+            --png "${ARTIFACTS}/failure.png" >/dev/null || true
+        echo "Screenshot: ${ARTIFACTS}/failure.png"
+    fi
+    echo "---- last 40 lines of ${LOG} ----"
+    tail -n 40 "$LOG" | tr -d '\r'
+    exit 1
+}
+
+# wait_for NAME PATTERN [grep flags]
+#
+# Wait until PATTERN (a fixed string) appears in the serial log after the
+# previous milestone's marker.
+wait_for() {
+    local name="$1" pattern="$2" flags="${3:--F}"
+    local deadline=$((SECONDS + TIMEOUT)) match
+
+    while true; do
+        match="$(tail -c +$((OFFSET + 1)) "$LOG" | tr -d '\r' \
+            | grep -b -m1 "$flags" -- "$pattern" || true)"
+        if [[ -n "$match" ]]; then
+            OFFSET=$((OFFSET + ${match%%:*} + 1))
+            STEP=$((STEP + 1))
+            printf '%2d. %-9s %4ds\n' "$STEP" "$name" $((SECONDS - START)) \
+                | tee -a "${ARTIFACTS}/milestones.txt"
+            return 0
+        fi
+        if grep -qF "[REPLAY] FAIL" "$LOG"; then
+            fail "$name: replay failed ($(grep -F '[REPLAY]' "$LOG" | tail -n 1 | tr -d '\r'))"
+        fi
+        if grep -qE "KERNEL PANIC|panicked at" "$LOG"; then
+            fail "$name: kernel panic"
+        fi
+        if ! kill -0 "$QEMU_PID" 2>/dev/null; then
+            fail "$name: QEMU exited"
+        fi
+        if (( SECONDS >= deadline )); then
+            fail "$name: no '$pattern' within ${TIMEOUT}s"
+        fi
+        sleep 1
+    done
+}
+
+# shot NAME -- screendump the current frame and check it
+shot() {
+    local name="$1" file
+    file="$(printf '%s/%02d-%s.ppm' "$ARTIFACTS" "$STEP" "$name")"
+    sleep 1     # let the frame after the marker reach the display
+    screendump "$file" || fail "$name: screendump failed"
+
+    local args=(--min-colors 16 --png "${file%.ppm}.png")
+    [[ -n "$PREV_SHOT" ]] && args+=(--differs-from "$PREV_SHOT")
+    python3 "${TESTING_DIR}/check-screenshot.py" "$file" "${args[@]}" \
+        || fail "$name: screenshot check failed"
+    PREV_SHOT="$file"
+}
+
+# =========================================================================
+# Milestones
+# =========================================================================
+
+echo "Desktop smoke test (artifacts: $ARTIFACTS)"
+
+wait_for boot "BOOTOK"
+wait_for shell "@veridian:"
+
+echo "inputrec queue ${RECORDING}${REFS:+ $REFS}" >&3
+wait_for queued "[REPLAY] Queued"
+
+echo "startgui builtin" >&3
+wait_for desktop "[DESKTOP] Entering compositor render loop"
+wait_for replay "[REPLAY] checkpoint 0:"
+shot desktop
+
+wait_for lock "[DESKTOP] Screen locked"
+shot lock
+
+wait_for login "[DESKTOP] Screen unlocked"
+shot login
+
+wait_for terminal "DESKTOP_SMOKE_TERMINAL_OK" -xF
+shot terminal
+
+wait_for window "[WM] Created window"
+sleep 1
+shot window
+
+wait_for finish "[REPLAY] PASS"
+
+grep -F "[REPLAY]" "$LOG" | tr -d '\r'
+echo "Desktop smoke test PASSED in $((SECONDS - START))s"
+exit 0
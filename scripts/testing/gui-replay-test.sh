@@ -0,0 +1,114 @@
+#!/usr/bin/env bash
+# gui-replay-test.sh -- Run a recorded desktop UI test under QEMU (x86_64)
+#
+# Boots VeridianOS with a framebuffer, starts the built-in desktop and waits
+# for the input replay to report its result on the serial console.
+#
+# The kernel must be built with the replay session on its command line, and
+# the recording plus reference screenshots must be present in the rootfs:
+#
+#   VERIDIAN_CMDLINE="input.replay=/usr/share/tests/ui/smoke.rec \
+#                     input.replay_refs=/usr/share/tests/ui/smoke" \
+#       ./build-kernel.sh x86_64 dev
+#
+# Recordings are made the same way with `input.record=<file>` (or the
+# `inputrec` shell command); press Ctrl+Alt+P to mark a checkpoint. Replay
+# screenshots are written to /tmp/replay/checkpoint-N.bmp in the guest.
+#
+# Usage:
+#   ./scripts/testing/gui-replay-test.sh [--timeout SECS] [--release] [--rootfs IMG]
+#
+# Exit status: 0 on "[REPLAY] PASS", 1 on "[REPLAY] FAIL", 2 on timeout.
+
+set -euo pipefail
+
+PROJECT_ROOT="$(cd "$(dirname "$0")/../.." && pwd)"
+
+TIMEOUT=180
+BUILD_MODE="debug"
+ROOTFS="${PROJECT_ROOT}/target/rootfs-blockfs.img"
+
+while [[ $# -gt 0 ]]; do
+    case "$1" in
+        --timeout) TIMEOUT="$2"; shift 2 ;;
+        --release) BUILD_MODE="release"; shift ;;
+        --rootfs)  ROOTFS="$2"; shift 2 ;;
+        --help|-h)
+            sed -n '2,21p' "$0" | sed 's/^# \{0,1\}//'
+            exit 0
+            ;;
+        *)
+            echo "Error: unknown option '$1'"
+            exit 2
+            ;;
+    esac
+done
+
+OVMF=""
+for candidate in \
+    /usr/share/edk2/x64/OVMF.4m.fd \
+    /usr/share/OVMF/OVMF_CODE.fd \
+    /usr/share/edk2/ovmf/OVMF_CODE.fd; do
+    if [[ -f "$candidate" ]]; then
+        OVMF="$candidate"
+        break
+    fi
+done
+if [[ -z "$OVMF" ]]; then
+    echo "Error: OVMF firmware not found (see scripts/run-veridian.sh)"
+    exit 2
+fi
+
+UEFI_IMG="${PROJECT_ROOT}/target/x86_64-veridian/${BUILD_MODE}/veridian-uefi.img"
+for file in "$UEFI_IMG" "$ROOTFS"; do
+    if [[ ! -f "$file" ]]; then
+        echo "Error: required file not found: $file"
+        exit 2
+    fi
+done
+
+LOG="$(mktemp -t veridian-replay.XXXXXX.log)"
+FIFO="$(mktemp -u -t veridian-replay.XXXXXX.in)"
+mkfifo "$FIFO"
+trap 'rm -f "$FIFO"; kill "$QEMU_PID" 2>/dev/null || true' EXIT
+
+qemu-system-x86_64 \
+    -drive "if=pflash,format=raw,readonly=on,file=${OVMF}" \
+    -drive "id=disk0,if=none,format=raw,file=${UEFI_IMG}" \
+    -device ide-hd,drive=disk0 \
+    -drive "file=${ROOTFS},if=none,id=vd0,format=raw,snapshot=on" \
+    -device virtio-blk-pci,drive=vd0 \
+    -m 2048M -vga std -display none \
+    -serial stdio <"$FIFO" >"$LOG" 2>&1 &
+QEMU_PID=$!
+exec 3>"$FIFO"
+
+echo "Waiting for shell prompt (log: $LOG)..."
+deadline=$((SECONDS + TIMEOUT))
+until grep -q "@veridian:" "$LOG"; do
+    if (( SECONDS >= deadline )); then
+        echo "TIMEOUT: shell did not start"
+        exit 2
+    fi
+    sleep 1
+done
+
+echo "startgui" >&3
+
+while (( SECONDS < deadline )); do
+    if grep -q "\[REPLAY\] PASS" "$LOG"; then
+        grep "\[REPLAY\]" "$LOG"
+        echo "GUI replay test PASSED"
+        exit 0
+    fi
+    if grep -q "\[REPLAY\] FAIL" "$LOG"; then
+        grep "\[REPLAY\]" "$LOG"
+        echo "GUI replay test FAILED"
+        exit 1
+    fi
+    sleep 1
+done
+
+grep "\[REPLAY\]" "$LOG" || true
+echo "TIMEOUT: replay did not finish within ${TIMEOUT}s"
+exit 2
@@ -0,0 +1,170 @@
+#!/usr/bin/env python3
+"""
+Compile a text input script into an input recording (.rec) for the kernel's
+record/replay facility (kernel/src/drivers/input_replay.rs).
+
+Recordings made by hand with `inputrec record` depend on how fast the
+person typed. A script gives the same event stream on every build, which is
+what a CI smoke test needs.
+
+    scripts/testing/mkrec.py scripts/testing/ui/desktop-smoke.input \\
+        -o rootfs/usr/share/tests/ui/desktop-smoke.rec
+
+Script syntax, one command per line ('#' starts a comment):
+
+    cursor X Y          starting cursor position (default 0 0)
+    pace MS             delay between generated events (default 40)
+    wait MS             advance the clock
+    key [MOD+]...NAME   press and release one key, e.g. "key ctrl+alt+l"
+    type TEXT           type the rest of the line literally
+    move DX DY          relative mouse motion
+    click [left|right|middle]
+    checkpoint          capture (and compare) a screenshot
+
+Key names are single printable characters or one of enter, esc, backspace,
+tab, space, up, down, left, right, home, end, delete. Modifiers are shift,
+ctrl, alt and super. Upper-case letters and shifted symbols typed with
+"type" carry the shift modifier, as they would from a real keyboard.
+"""
+
+import argparse
+import struct
+import sys
+
+MAGIC = b"VIRP"
+VERSION = 1
+
+EV_KEY = 0x01
+EV_REL = 0x02
+EV_CHECKPOINT = 0x7F00
+REL_X = 0x00
+REL_Y = 0x01
+BUTTONS = {"left": 0x110, "right": 0x111, "middle": 0x112}
+
+MODIFIERS = {"shift": 0x01, "ctrl": 0x02, "alt": 0x04, "super": 0x08}
+
+KEYS = {
+    "enter": 0x0D,
+    "esc": 0x1B,
+    "backspace": 0x08,
+    "tab": 0x09,
+    "space": 0x20,
+    "up": 0x80,
+    "down": 0x81,
+    "left": 0x82,
+    "right": 0x83,
+    "home": 0x84,
+    "end": 0x85,
+    "delete": 0x86,
+}
+
+SHIFTED = set('~!@#$%^&*()_+{}|:"<>?')
+
+
+class ScriptError(Exception):
+    pass
+
+
+class Recorder:
+    def __init__(self):
+        self.cursor = (0, 0)
+        self.pace = 40
+        self.clock = 0
+        self.checkpoints = 0
+        self.records = []
+
+    def emit(self, event_type, code, value, modifiers=0):
+        self.records.append((self.clock, event_type, code, value, modifiers))
+        self.clock += self.pace
+
+    def key(self, code, modifiers):
+        self.emit(EV_KEY, code, 1, modifiers)
+        self.emit(EV_KEY, code, 0, modifiers)
+
+    def encode(self):
+        out = bytearray(MAGIC)
+        out += struct.pack("<HHiiI", VERSION, 0, self.cursor[0], self.cursor[1],
+                           len(self.records))
+        for offset, event_type, code, value, modifiers in self.records:
+            out += struct.pack("<IHHiB3x", offset, event_type, code, value, modifiers)
+        return bytes(out)
+
+
+def parse_key(spec):
+    *mods, name = spec.split("+")
+    modifiers = 0
+    for mod in mods:
+        if mod not in MODIFIERS:
+            raise ScriptError(f"unknown modifier '{mod}'")
+        modifiers |= MODIFIERS[mod]
+    if name in KEYS:
+        return KEYS[name], modifiers
+    if len(name) == 1 and 0x21 <= ord(name) <= 0x7E:
+        return ord(name), modifiers
+    raise ScriptError(f"unknown key '{name}'")
+
+
+def compile_script(lines):
+    rec = Recorder()
+    for lineno, raw in enumerate(lines, 1):
+        line = raw.rstrip("\n")
+        command, _, rest = line.strip().partition(" ")
+        if command != "type":
+            rest = rest.split("#", 1)[0].strip()
+        if not command or command.startswith("#"):
+            continue
+        args = rest.split()
+        try:
+            if command == "cursor" and len(args) == 2:
+                rec.cursor = (int(args[0]), int(args[1]))
+            elif command == "pace" and len(args) == 1:
+                rec.pace = int(args[0])
+            elif command == "wait" and len(args) == 1:
+                rec.clock += int(args[0])
+            elif command == "key" and len(args) == 1:
+                rec.key(*parse_key(args[0].lower()))
+            elif command == "type" and rest:
+                for ch in rest:
+                    shift = ch.isupper() or ch in SHIFTED
+                    rec.key(ord(ch), MODIFIERS["shift"] if shift else 0)
+            elif command == "move" and len(args) == 2:
+                rec.emit(EV_REL, REL_X, int(args[0]))
+                rec.emit(EV_REL, REL_Y, int(args[1]))
+            elif command == "click" and len(args) <= 1:
+                button = BUTTONS.get(args[0] if args else "left")
+                if button is None:
+                    raise ScriptError(f"unknown button '{args[0]}'")
+                rec.emit(EV_KEY, button, 1)
+                rec.emit(EV_KEY, button, 0)
+            elif command == "checkpoint" and not args:
+                rec.emit(EV_CHECKPOINT, rec.checkpoints, 0)
+                rec.checkpoints += 1
+            else:
+                raise ScriptError(f"bad command '{line.strip()}'")
+        except (ScriptError, ValueError) as e:
+            raise ScriptError(f"line {lineno}: {e}") from None
+    return rec
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.split("\n\n")[0].strip())
+    parser.add_argument("script", help="input script")
+    parser.add_argument("-o", "--output", required=True, help="recording to write")
+    args = parser.parse_args()
+
+    try:
+        with open(args.script) as f:
+            rec = compile_script(f)
+    except ScriptError as e:
+        print(f"mkrec: {args.script}: {e}", file=sys.stderr)
+        return 1
+
+    with open(args.output, "wb") as f:
+        f.write(rec.encode())
+    print(f"{args.output}: {len(rec.records)} events, {rec.checkpoints} checkpoints, "
+          f"{rec.clock / 1000:.1f}s")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+# Regenerate the TLS root store bundled into veridian-std.
+#
+# Copies a fixed list of Web PKI root certificates from the host's CA
+# directory into userland/rust-std/src/tls/roots.pem, which the TLS client
+# embeds with include_str!. Review the diff before committing: every
+# certificate added here is trusted for every HTTPS connection.
+#
+# Usage: scripts/update-tls-roots.sh [ca-dir]   (default /etc/ssl/certs)
+
+set -euo pipefail
+
+CA_DIR="${1:-/etc/ssl/certs}"
+OUT="$(cd "$(dirname "$0")/.." && pwd)/userland/rust-std/src/tls/roots.pem"
+
+# Roots behind the package mirrors and the large public CAs. File names follow
+# the Debian/Fedora ca-certificates layout.
+ROOTS=(
+    "ISRG Root X1"
+    "ISRG Root X2"
+    "DigiCert Global Root CA"
+    "DigiCert Global Root G2"
+    "DigiCert Global Root G3"
+    "GTS Root R1"
+    "GTS Root R2"
+    "GTS Root R3"
+    "GTS Root R4"
+    "Amazon Root CA 1"
+    "Amazon Root CA 2"
+    "Amazon Root CA 3"
+    "Amazon Root CA 4"
+    "GlobalSign Root CA - R3"
+    "GlobalSign Root E46"
+    "GlobalSign Root R46"
+    "USERTrust RSA Certification Authority"
+    "USERTrust ECC Certification Authority"
+    "Microsoft RSA Root Certificate Authority 2017"
+    "Microsoft ECC Root Certificate Authority 2017"
+    "Starfield Root Certificate Authority - G2"
+    "Go Daddy Root Certificate Authority - G2"
+)
+
+{
+    echo "# VeridianOS bundled TLS trust anchors."
+    echo "# Generated by scripts/update-tls-roots.sh; do not edit by hand."
+    for name in "${ROOTS[@]}"; do
+        file="$CA_DIR/${name// /_}.pem"
+        if [[ ! -f "$file" ]]; then
+            echo "error: $file not found" >&2
+            exit 1
+        fi
+        echo
+        echo "# $name"
+        sed -n '/-----BEGIN CERTIFICATE-----/,/-----END CERTIFICATE-----/p' "$file"
+    done
+} > "$OUT"
+
+echo "wrote ${#ROOTS[@]} roots to $OUT"
@@ -3,7 +3,8 @@
 #
 # Covers: $(( )), let, arithmetic for loops, operators (+, -, *, /, %,
 #         **, comparison, bitwise, logical), ternary, parentheses,
-#         hex/octal literals, variable references in arithmetic.
+#         hex/octal/base#n literals, variable references in arithmetic,
+#         assignment and increment operators, short-circuit evaluation.
 
 SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
 . "$SCRIPT_DIR/framework.sh"
@@ -299,6 +300,14 @@ _TEST_NAME="arith: decimal zero"
 _out=$($VSH -c 'echo $((0))')
 assert_equal "$_out" "0"
 
+_TEST_NAME="arith: base#digits literals"
+_out=$($VSH -c 'echo $((2#1010)) $((16#ff)) $((36#z)) $((64#_))')
+assert_equal "$_out" "10 255 35 63"
+
+_TEST_NAME="arith: digit too large for base is an error"
+_out=$($VSH -c 'echo $((08))' 2>&1)
+assert_contains "$_out" "08:"
+
 # ---------------------------------------------------------------------------
 # let builtin
 # ---------------------------------------------------------------------------
@@ -368,6 +377,90 @@ _TEST_NAME="arith assign: decrement"
 _out=$($VSH -c 'X=10; X=$((X-1)); echo $X')
 assert_equal "$_out" "9"
 
+# ---------------------------------------------------------------------------
+# Assignment operators
+# ---------------------------------------------------------------------------
+
+_TEST_NAME="arith: assignment in \$(( ))"
+_out=$($VSH -c 'echo $((X = 4 * 2)); echo $X')
+assert_equal "$_out" "8
+8"
+
+_TEST_NAME="arith: compound assignments"
+_out=$($VSH -c 'X=10; (( X += 5, X *= 2, X -= 6, X /= 3, X %= 7, X <<= 2, X |= 1 )); echo $X')
+assert_equal "$_out" "5"
+
+_TEST_NAME="arith: chained assignment"
+_out=$($VSH -c '(( A = B = 3 )); echo $A $B')
+assert_equal "$_out" "3 3"
+
+_TEST_NAME="arith: post-increment returns old value"
+_out=$($VSH -c 'I=5; echo $((I++)); echo $I')
+assert_equal "$_out" "5
+6"
+
+_TEST_NAME="arith: pre-decrement returns new value"
+_out=$($VSH -c 'I=5; echo $((--I)); echo $I')
+assert_equal "$_out" "4
+4"
+
+_TEST_NAME="arith: increment in (( ))"
+_out=$($VSH -c 'N=0; (( N++ )); (( N++ )); echo $N')
+assert_equal "$_out" "2"
+
+_TEST_NAME="arith: 5--2 is a subtraction"
+_out=$($VSH -c 'echo $((5--2))')
+assert_equal "$_out" "7"
+
+_TEST_NAME="let: assigns variables"
+_out=$($VSH -c 'let X=6 "Y = X * 7"; echo $Y')
+assert_equal "$_out" "42"
+
+_TEST_NAME="arith: array element assignment"
+_out=$($VSH -c 'A=(1 2 3); (( A[1] += 10 )); echo ${A[1]}')
+assert_equal "$_out" "12"
+
+_TEST_NAME="arith: variable holding an expression"
+_out=$($VSH -c 'E="2 + 3"; echo $((E * 2))')
+assert_equal "$_out" "10"
+
+# ---------------------------------------------------------------------------
+# Short-circuit evaluation
+# ---------------------------------------------------------------------------
+
+_TEST_NAME="arith: && skips right side"
+_out=$($VSH -c 'X=1; (( 0 && (X = 2) )); echo $X')
+assert_equal "$_out" "1"
+
+_TEST_NAME="arith: || skips right side"
+_out=$($VSH -c 'X=1; (( 1 || (X = 2) )); echo $X')
+assert_equal "$_out" "1"
+
+_TEST_NAME="arith: ternary evaluates one branch"
+_out=$($VSH -c 'X=0; Y=0; (( 1 ? (X = 5) : (Y = 5) )); echo $X $Y')
+assert_equal "$_out" "5 0"
+
+_TEST_NAME="arith: untaken branch may divide by zero"
+_out=$($VSH -c 'echo $(( 0 ? 1 / 0 : 4 ))')
+assert_equal "$_out" "4"
+
+_TEST_NAME="(( )): division by zero fails"
+assert_exit_code 1 $VSH -c '(( 1 / 0 ))'
+
+# ---------------------------------------------------------------------------
+# Arithmetic for loops
+# ---------------------------------------------------------------------------
+
+_TEST_NAME="for (( )): counts up"
+_out=$($VSH -c 'for (( i = 0; i < 3; i++ )); do echo $i; done')
+assert_equal "$_out" "0
+1
+2"
+
+_TEST_NAME="for (( )): loop variable keeps final value"
+_out=$($VSH -c 'for (( i = 10; i > 0; i -= 4 )); do :; done; echo $i')
+assert_equal "$_out" "-2"
+
 # ---------------------------------------------------------------------------
 # Comma operator
 # ---------------------------------------------------------------------------
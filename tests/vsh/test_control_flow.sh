@@ -3,7 +3,8 @@
 #
 # Covers: if/then/else/elif/fi, while/do/done, until/do/done,
 #         for/in/do/done, case/esac, nested loops, break, continue,
-#         arithmetic for loops, select (basic).
+#         arithmetic for loops, select (basic), [[ ]] with pattern and
+#         regex matching.
 
 SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
 . "$SCRIPT_DIR/framework.sh"
@@ -353,6 +354,49 @@ assert_exit_code 0 $VSH -c '[[ -z "" || -n "b" ]]'
 _TEST_NAME="[[ ]]: negation"
 assert_exit_code 0 $VSH -c '[[ ! -z "text" ]]'
 
+_TEST_NAME="[[ ]]: no word splitting of operands"
+assert_exit_code 0 $VSH -c 'x="a b"; [[ $x == "a b" ]]'
+
+_TEST_NAME="[[ ]]: == matches a glob pattern"
+assert_exit_code 0 $VSH -c 'f=dir/notes.txt; [[ $f == *.txt ]]'
+
+_TEST_NAME="[[ ]]: quoted pattern is literal"
+assert_exit_code 1 $VSH -c 'f=notes.txt; [[ $f == "*.txt" ]]'
+
+_TEST_NAME="[[ ]]: pattern from a variable"
+assert_exit_code 0 $VSH -c 'p="n?tes.*"; [[ notes.txt == $p ]]'
+
+_TEST_NAME="[[ ]]: != with a pattern"
+assert_exit_code 0 $VSH -c '[[ abc != a[xy]* ]]'
+
+_TEST_NAME="[[ ]]: string ordering"
+assert_exit_code 0 $VSH -c '[[ apple < banana && pear > orange ]]'
+
+_TEST_NAME="[[ ]]: -lt evaluates arithmetic"
+assert_exit_code 0 $VSH -c 'n=4; [[ n+1 -eq 5 && -5 -lt 3 ]]'
+
+_TEST_NAME="[[ ]]: parenthesized group"
+assert_exit_code 0 $VSH -c '[[ ( -z "" || -z x ) && -n y ]]'
+
+_TEST_NAME="[[ ]]: =~ matches"
+assert_exit_code 0 $VSH -c '[[ abc123 =~ ^[a-z]+[0-9]{3}$ ]]'
+
+_TEST_NAME="[[ ]]: =~ does not match"
+assert_exit_code 1 $VSH -c '[[ abc =~ ^[0-9]+$ ]]'
+
+_TEST_NAME="[[ ]]: =~ sets BASH_REMATCH"
+_out=$($VSH -c '[[ "v 12-345" =~ ([0-9]+)-([0-9]+) ]]; echo ${BASH_REMATCH[0]} ${BASH_REMATCH[1]} ${BASH_REMATCH[2]}')
+assert_equal "$_out" "12-345 12 345"
+
+_TEST_NAME="[[ ]]: =~ alternation and groups"
+assert_exit_code 0 $VSH -c '[[ abba =~ ^(a|b)+$ ]]'
+
+_TEST_NAME="[[ ]]: =~ quoted part is literal"
+assert_exit_code 1 $VSH -c '[[ axc =~ "a.c" ]]'
+
+_TEST_NAME="[[ ]]: =~ bracket class"
+assert_exit_code 0 $VSH -c '[[ foo_1 =~ ^[[:alpha:]_][[:alnum:]_]*$ ]]'
+
 # ---------------------------------------------------------------------------
 # Arithmetic evaluation: (( ))
 # ---------------------------------------------------------------------------
@@ -3,7 +3,8 @@
 #
 # Covers: {a,b,c}, {1..10}, {a..z}, {01..10..2}, tilde (~, ~+, ~-),
 #         ${var:-default}, ${#var}, ${var%pat}, ${var/pat/rep},
-#         ${var:offset:length}, ${var^}, ${var,,}, and more.
+#         ${var:offset:length}, ${var^}, ${var,,}, command substitution
+#         ($(...), `...`), process substitution (<(...), >(...)), and more.
 
 SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
 . "$SCRIPT_DIR/framework.sh"
@@ -346,6 +347,92 @@ _TEST_NAME="arith: nested in parameter expansion"
 _out=$($VSH -c 'X=hello; echo ${X:$((1+1)):2}')
 assert_equal "$_out" "ll"
 
+# ---------------------------------------------------------------------------
+# Command substitution
+# ---------------------------------------------------------------------------
+
+_TEST_NAME="cmdsub: \$(...) basic"
+_out=$($VSH -c 'echo $(echo hello)')
+assert_equal "$_out" "hello"
+
+_TEST_NAME="cmdsub: backquotes"
+_out=$($VSH -c 'echo `echo hello`')
+assert_equal "$_out" "hello"
+
+_TEST_NAME="cmdsub: escaped backquote inside backquotes"
+_out=$($VSH -c 'echo `echo \`echo inner\``')
+assert_equal "$_out" "inner"
+
+_TEST_NAME="cmdsub: nested \$(...)"
+_out=$($VSH -c 'echo $(echo $(echo deep))')
+assert_equal "$_out" "deep"
+
+_TEST_NAME="cmdsub: trailing newlines removed"
+_out=$($VSH -c 'X=$(printf "a\n\n\n"); echo "[$X]"')
+assert_equal "$_out" "[a]"
+
+_TEST_NAME="cmdsub: unquoted result is split"
+_out=$($VSH -c 'set -- $(echo "a   b"); echo $#')
+assert_equal "$_out" "2"
+
+_TEST_NAME="cmdsub: quoted result is not split"
+_out=$($VSH -c 'echo "$(echo "a   b")"')
+assert_equal "$_out" "a   b"
+
+_TEST_NAME="cmdsub: assignment is not split"
+_out=$($VSH -c 'X=$(echo "a   b"); echo "$X"')
+assert_equal "$_out" "a   b"
+
+_TEST_NAME="cmdsub: closing paren inside quotes"
+_out=$($VSH -c 'echo $(echo ")")')
+assert_equal "$_out" ")"
+
+_TEST_NAME="cmdsub: subshell inside \$(...)"
+_out=$($VSH -c 'echo $( (echo sub) )')
+assert_equal "$_out" "sub"
+
+_TEST_NAME="cmdsub: output is not expanded again"
+_out=$($VSH -c 'X=no; echo "$(echo '"'"'$X'"'"')"')
+assert_equal "$_out" '$X'
+
+_TEST_NAME="cmdsub: sees shell functions"
+_out=$($VSH -c 'f() { echo fn; }; echo $(f)')
+assert_equal "$_out" "fn"
+
+_TEST_NAME="cmdsub: assignment takes its exit status"
+_out=$($VSH -c 'X=$(false); echo $?')
+assert_equal "$_out" "1"
+
+_TEST_NAME="cmdsub: does not change the parent shell"
+_out=$($VSH -c 'X=1; Y=$(X=2; echo $X); echo $X$Y')
+assert_equal "$_out" "12"
+
+# ---------------------------------------------------------------------------
+# Process substitution
+# ---------------------------------------------------------------------------
+
+_TEST_NAME="procsub: <(...) as an input file"
+_out=$($VSH -c 'cat <(echo from-procsub)')
+assert_equal "$_out" "from-procsub"
+
+_TEST_NAME="procsub: two inputs"
+_out=$($VSH -c 'cat <(echo one) <(echo two)')
+assert_equal "$_out" "one
+two"
+
+_TEST_NAME="procsub: redirect from <(...)"
+_out=$($VSH -c 'while read l; do echo "got $l"; done < <(printf "a\nb\n")')
+assert_equal "$_out" "got a
+got b"
+
+_TEST_NAME="procsub: expands to a path"
+_out=$($VSH -c 'echo <(true)')
+assert_match "$_out" "^/dev/fd/[0-9]+$"
+
+_TEST_NAME="procsub: >(...) as an output file"
+_out=$($VSH -c 'echo into > >(cat); sleep 1')
+assert_equal "$_out" "into"
+
 # ---------------------------------------------------------------------------
 # Special variables
 # ---------------------------------------------------------------------------
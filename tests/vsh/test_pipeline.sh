@@ -1,7 +1,8 @@
 #!/bin/sh
 # test_pipeline.sh -- Tests for pipelines and command lists in vsh
 #
-# Covers: simple pipes (|), multi-stage pipelines, pipefail,
+# Covers: simple pipes (|), multi-stage pipelines, pipefail, PIPESTATUS,
+#         builtins and functions in pipelines, |&,
 #         && and || operators, semicolon command lists,
 #         pipeline exit status, negation (!).
 
@@ -106,6 +107,38 @@ _TEST_NAME="pipe: \$? reflects last in pipeline"
 _out=$($VSH -c 'false | true; echo $?')
 assert_equal "$_out" "0"
 
+_TEST_NAME="pipe: exit inside a stage is its status"
+_out=$($VSH -c 'true | exit 3; echo $?')
+assert_equal "$_out" "3"
+
+_TEST_NAME="pipe: command not found in a stage"
+_out=$($VSH -c 'echo x | no_such_command_vsh 2>/dev/null; echo $?')
+assert_equal "$_out" "127"
+
+# ---------------------------------------------------------------------------
+# PIPESTATUS
+# ---------------------------------------------------------------------------
+
+_TEST_NAME="pipestatus: all stages"
+_out=$($VSH -c 'true | false | (exit 4); echo ${PIPESTATUS[@]}')
+assert_equal "$_out" "0 1 4"
+
+_TEST_NAME="pipestatus: single element"
+_out=$($VSH -c 'true | false; echo ${PIPESTATUS[1]}')
+assert_equal "$_out" "1"
+
+_TEST_NAME="pipestatus: element count"
+_out=$($VSH -c 'true | true | true | true; echo ${#PIPESTATUS[@]}')
+assert_equal "$_out" "4"
+
+_TEST_NAME="pipestatus: simple command"
+_out=$($VSH -c 'false; echo ${PIPESTATUS[@]}')
+assert_equal "$_out" "1"
+
+_TEST_NAME="pipestatus: unaffected by negation"
+_out=$($VSH -c '! false | true; echo ${PIPESTATUS[@]}')
+assert_equal "$_out" "1 0"
+
 # ---------------------------------------------------------------------------
 # Pipefail
 # ---------------------------------------------------------------------------
@@ -296,6 +329,37 @@ _TEST_NAME="edge: single command (no pipe)"
 _out=$($VSH -c 'echo solo')
 assert_equal "$_out" "solo"
 
+_TEST_NAME="pipe: ls | grep | wc -l"
+mkdir -p "$TMPDIR/lsdir"
+touch "$TMPDIR/lsdir/foo1" "$TMPDIR/lsdir/foo2" "$TMPDIR/lsdir/bar"
+_out=$($VSH -c "ls $TMPDIR/lsdir | grep foo | wc -l")
+assert_equal "$(echo $_out)" "2"
+
+_TEST_NAME="pipe: builtin feeds external"
+_out=$($VSH -c 'printf "%s\n" one two three | wc -l')
+assert_equal "$(echo $_out)" "3"
+
+_TEST_NAME="pipe: builtin reads pipe"
+_out=$($VSH -c 'echo hello | read line; echo "[$line]"')
+# read runs in a subshell, so the variable is not set in the parent
+assert_equal "$_out" "[]"
+
+_TEST_NAME="pipe: function in pipeline"
+_out=$($VSH -c 'f() { echo a; echo b; }; f | wc -l')
+assert_equal "$(echo $_out)" "2"
+
+_TEST_NAME="pipe: pwd in pipeline"
+_out=$($VSH -c 'cd /; pwd | cat')
+assert_equal "$_out" "/"
+
+_TEST_NAME="pipe: |& carries stderr"
+_out=$($VSH -c 'ls /no/such/path/vsh |& wc -l')
+assert_not_equal "$(echo $_out)" "0"
+
+_TEST_NAME="pipe: builtin output not on terminal"
+_out=$($VSH -c 'echo hidden | true')
+assert_equal "$_out" ""
+
 _TEST_NAME="edge: pipe with builtin"
 _out=$($VSH -c 'echo hello | echo world')
 # echo ignores stdin, so output should be "world" from the last echo
@@ -134,6 +134,59 @@ value: \$X
 EOF")
 assert_equal "$_out" 'value: $X'
 
+_TEST_NAME="redir: << heredoc with partly quoted delimiter"
+_out=$($VSH -c 'X=v; cat <<E"O"F
+$X
+EOF')
+assert_equal "$_out" '$X'
+
+_TEST_NAME="redir: << heredoc keeps quotes"
+_out=$($VSH -c 'X=v; cat <<EOF
+'"'"'$X'"'"' "$X"
+EOF')
+assert_equal "$_out" "'v' \"v\""
+
+_TEST_NAME="redir: << heredoc backslash escapes"
+_out=$($VSH -c 'X=v; cat <<EOF
+\$X \\ \n
+EOF')
+assert_equal "$_out" '$X \ \n'
+
+_TEST_NAME="redir: << heredoc with command substitution"
+_out=$($VSH -c 'cat <<EOF
+[$(echo sub)]
+EOF')
+assert_equal "$_out" "[sub]"
+
+_TEST_NAME="redir: <<- strips leading tabs"
+_out=$($VSH -c "$(printf 'cat <<-EOF\n\t\tindented\n\tEOF\necho after')")
+assert_equal "$_out" "indented
+after"
+
+_TEST_NAME="redir: two heredocs on one line"
+_out=$($VSH -c 'cat <<A; cat <<B
+first
+A
+second
+B')
+assert_equal "$_out" "first
+second"
+
+_TEST_NAME="redir: heredoc in a pipeline"
+_out=$($VSH -c 'cat <<EOF | wc -l
+a
+b
+EOF')
+assert_equal "$(echo $_out)" "2"
+
+_TEST_NAME="redir: commands after a heredoc"
+_out=$($VSH -c 'cat <<EOF
+body
+EOF
+echo next')
+assert_equal "$_out" "body
+next"
+
 _TEST_NAME="redir: << heredoc preserves whitespace"
 _out=$($VSH -c 'cat <<EOF
   indented
@@ -233,6 +233,51 @@ extern "C" {
 /** Transport endpoint is already connected */
 #define EISCONN             65
 
+/** Address family not supported */
+#define EAFNOSUPPORT        66
+
+/** Connection aborted */
+#define ECONNABORTED        67
+
+/** No route to host */
+#define EHOSTUNREACH        68
+
+/** Network is down */
+#define ENETDOWN            69
+
+/** Network dropped connection because of reset */
+#define ENETRESET           70
+
+/** Protocol not available */
+#define ENOPROTOOPT         71
+
+/** No such device */
+#define ENODEV              72
+
+/** Value too large for defined data type */
+#define EOVERFLOW           73
+
+/** Protocol error */
+#define EPROTO              74
+
+/** Operation canceled */
+#define ECANCELED           75
+
+/** Owner died */
+#define EOWNERDEAD          76
+
+/** State not recoverable */
+#define ENOTRECOVERABLE     77
+
+/** Link has been severed */
+#define ENOLINK             78
+
+/** Resource limit exceeded (process table full, fd table full) */
+#define ERESOURCELIMIT      79  /* SyscallError::ResourceLimitExceeded = -79 */
+
+/** No data available (extended attribute not found) */
+#define ENODATA             80  /* SyscallError::NoData = -80 */
+
 /* ========================================================================= */
 /* POSIX-Compatible Aliases                                                  */
 /* ========================================================================= */
@@ -240,6 +285,7 @@ extern "C" {
 /** Same as EAGAIN (POSIX compatibility) */
 #define EWOULDBLOCK         EAGAIN
 #define EDEADLOCK           EDEADLK
+#define ENOATTR             ENODATA
 
 /* ========================================================================= */
 /* errno access                                                              */
@@ -4,7 +4,8 @@
  * Copyright (c) 2025-2026 VeridianOS Contributors
  * SPDX-License-Identifier: MIT OR Apache-2.0
  *
- * Syscall numbers and inline assembly wrappers matching kernel/src/syscall/mod.rs.
+ * Syscall numbers and inline assembly wrappers matching libs/veridian-abi,
+ * the syscall table the kernel is built from (its tests check this file).
  * Architecture-specific calling conventions:
  *   x86_64:  syscall instruction, nr in rax, args in rdi/rsi/rdx/r10/r8/r9
  *   aarch64: svc #0, nr in x8, args in x0-x5
@@ -24,7 +25,7 @@ extern "C" {
 /* Syscall Numbers                                                           */
 /* ========================================================================= */
 
-/* IPC system calls (0-7) */
+/* IPC system calls (0-8) */
 #define SYS_IPC_SEND            0
 #define SYS_IPC_RECEIVE         1
 #define SYS_IPC_CALL            2
@@ -33,6 +34,7 @@ extern "C" {
 #define SYS_IPC_BIND_ENDPOINT   5
 #define SYS_IPC_SHARE_MEMORY   6
 #define SYS_IPC_MAP_MEMORY      7
+#define SYS_IPC_LOOKUP_ENDPOINT 8
 
 /* Process management (10-18) */
 #define SYS_PROCESS_YIELD       10
@@ -51,9 +53,11 @@ extern "C" {
 #define SYS_MEMORY_PROTECT      22
 #define SYS_MEMORY_BRK          23
 
-/* Capability management (30-31) */
+/* Capability management (30-33) */
 #define SYS_CAPABILITY_GRANT    30
 #define SYS_CAPABILITY_REVOKE   31
+#define SYS_CAP_INSPECT         32
+#define SYS_AUDIT_READ          33
 
 /* Thread management (40-46) */
 #define SYS_THREAD_CREATE       40
@@ -90,8 +94,13 @@ extern "C" {
 #define SYS_FS_UNMOUNT          71
 #define SYS_FS_SYNC             72
 
-/* Kernel information (80) */
+/* Kernel information (80, 86) */
 #define SYS_KERNEL_GET_INFO     80
+#define SYS_ABI_VERSION         86
+
+/* SYS_ABI_VERSION argument and result: (major << 16) | minor, 0 = query */
+#define VERIDIAN_ABI_MAJOR      1
+#define VERIDIAN_ABI_MINOR      5
 
 /* Package management (90-94) */
 #define SYS_PKG_INSTALL         90
@@ -150,6 +159,10 @@ extern "C" {
 #define SYS_SETSID              179
 #define SYS_GETSID              180
 
+/* Supplementary groups (181-182) */
+#define SYS_GETGROUPS           181
+#define SYS_SETGROUPS           182
+
 /* Scatter/gather I/O (183-184) */
 #define SYS_READV               183
 #define SYS_WRITEV              184
@@ -184,6 +197,26 @@ extern "C" {
 /* System information (204) */
 #define SYS_PROCESS_UNAME       204
 
+/* Pseudo-terminals (280-283) */
+#define SYS_OPENPTY             280
+#define SYS_GRANTPT             281
+#define SYS_UNLOCKPT            282
+#define SYS_PTSNAME             283
+
+/* Cryptographic primitives (kernel/src/syscall/crypto.rs) */
+#define SYS_CRYPTO_HASH         363
+#define SYS_CRYPTO_HMAC         364
+#define SYS_CRYPTO_AEAD_SEAL    365
+#define SYS_CRYPTO_AEAD_OPEN    366
+#define SYS_CRYPTO_X25519       367
+#define SYS_CRYPTO_VERIFY       368
+#define SYS_CRYPTO_SIGN         369
+
+/* IPC namespace supervision (kernel/src/ipc/namespace.rs) */
+#define SYS_IPC_NAMESPACE_CREATE 384
+#define SYS_IPC_NAMESPACE_ASSIGN 385
+#define SYS_IPC_NAMESPACE_EXPORT 386
+
 /* AT_* constants for *at() syscalls */
 #define AT_FDCWD                (-100)
 #define AT_REMOVEDIR            0x200
@@ -0,0 +1,477 @@
+//! BlockFS image checker
+//!
+//! Parses a raw image with the same rules as the kernel's BlockFS driver and
+//! verifies the invariants a mountable filesystem must hold:
+//! - the superblock describes a layout that fits the image, in a format version
+//!   and feature set the kernel can read
+//! - every block an inode references lies in the data area, is marked in the
+//!   bitmap and belongs to exactly one inode (no double allocation)
+//! - every block marked in the bitmap is referenced (no leaks), and the
+//!   superblock's free counts match the bitmap and inode table
+//! - directories parse cleanly, start with `.` and `..`, hold unique names
+//!   pointing at live inodes of the right type, and form a tree
+//! - link counts match the directory entries and no inode is orphaned
+//!
+//! The parser never trusts a field: corrupted images produce problems, not
+//! panics, which is what the mutation fuzzer relies on.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use blockfs_format::{
+    computed_first_data_block, decode_xattr_block, inode_location, DirEntryHeader, DiskInode,
+    Superblock, BLOCK_SIZE, DIRECT_BLOCKS, DIR_ENTRY_HEADER_SIZE, FT_DIR, FT_REG_FILE, FT_SYMLINK,
+    PTRS_PER_BLOCK, ROOT_INODE, S_IFDIR, S_IFLNK, S_IFMT, S_IFREG,
+};
+
+use crate::{Entry, Tree};
+
+/// Largest file whose contents the checker reads back. Bigger files are
+/// still checked block by block.
+pub const MAX_VERIFIED_FILE: u64 = 64 * 1024 * 1024;
+
+fn u32_at(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn in_use(inode: &DiskInode) -> bool {
+    inode.mode != 0
+}
+
+/// Stricter than the kernel's `is_dir()` and friends: the whole type field
+/// must match.
+fn has_type(inode: &DiskInode, kind: u16) -> bool {
+    inode.mode & S_IFMT == kind
+}
+
+struct Checker<'a> {
+    image: &'a [u8],
+    sb: Superblock,
+    inodes: Vec<DiskInode>,
+    /// Inode owning each block, for blocks referenced so far
+    owner: BTreeMap<u32, u32>,
+    /// Logical-to-physical map of every in-use inode (0 = hole)
+    maps: BTreeMap<u32, Vec<u32>>,
+    problems: Vec<String>,
+}
+
+/// Check `image` and return the tree it holds, or every problem found.
+pub fn check(image: &[u8]) -> Result<Tree, Vec<String>> {
+    let mut checker = Checker::open(image).map_err(|p| vec![p])?;
+    checker.check_blocks();
+    let tree = checker.check_tree();
+    if checker.problems.is_empty() {
+        Ok(tree)
+    } else {
+        Err(checker.problems)
+    }
+}
+
+impl<'a> Checker<'a> {
+    fn open(image: &'a [u8]) -> Result<Self, String> {
+        if image.len() < BLOCK_SIZE {
+            return Err(format!("image of {} bytes has no superblock", image.len()));
+        }
+        let sb = Superblock::decode(image);
+        if !sb.is_valid() {
+            return Err(format!("bad magic {:#x}", sb.magic));
+        }
+        let Ok(format) = sb.inode_format() else {
+            return Err(format!(
+                "version {} with incompatible features {:#x} and inode size {} unsupported",
+                sb.version, sb.feature_incompat, sb.inode_size
+            ));
+        };
+        if sb.block_size as usize != BLOCK_SIZE {
+            return Err(format!("block size {} unsupported", sb.block_size));
+        }
+        if sb.block_count as u64 * BLOCK_SIZE as u64 > image.len() as u64 {
+            return Err(format!(
+                "{} blocks do not fit in a {} byte image",
+                sb.block_count,
+                image.len()
+            ));
+        }
+        if sb.inode_count == 0 {
+            return Err("no inodes".to_string());
+        }
+        let expected_first = computed_first_data_block(sb.block_count, sb.inode_count, format);
+        if sb.first_data_block != expected_first || expected_first >= sb.block_count {
+            return Err(format!(
+                "first data block {} (layout needs {}) in {} blocks",
+                sb.first_data_block, expected_first, sb.block_count
+            ));
+        }
+
+        let inodes = (0..sb.inode_count)
+            .map(|i| {
+                let (block, off) = inode_location(sb.block_count, i, format);
+                DiskInode::decode(&image[block as usize * BLOCK_SIZE + off..], format)
+            })
+            .collect();
+
+        Ok(Self {
+            image,
+            sb,
+            inodes,
+            owner: BTreeMap::new(),
+            maps: BTreeMap::new(),
+            problems: Vec::new(),
+        })
+    }
+
+    fn block(&self, block: u32) -> &'a [u8] {
+        let start = block as usize * BLOCK_SIZE;
+        &self.image[start..start + BLOCK_SIZE]
+    }
+
+    fn allocated(&self, block: u32) -> bool {
+        let byte = BLOCK_SIZE + block as usize / 8;
+        self.image[byte] & (1 << (block % 8)) != 0
+    }
+
+    /// Record that `inode` uses `block`; false if the block is unusable.
+    fn claim(&mut self, inode: u32, block: u32, what: &str) -> bool {
+        if block < self.sb.first_data_block || block >= self.sb.block_count {
+            self.problems.push(format!(
+                "inode {}: {} block {} outside the data area",
+                inode, what, block
+            ));
+            return false;
+        }
+        if !self.allocated(block) {
+            self.problems.push(format!(
+                "inode {}: {} block {} is free in the bitmap",
+                inode, what, block
+            ));
+        }
+        if let Some(other) = self.owner.insert(block, inode) {
+            self.problems.push(format!(
+                "block {} allocated twice (inodes {} and {})",
+                block, other, inode
+            ));
+        }
+        true
+    }
+
+    /// Claim a pointer block and return its non-zero entries by index.
+    fn pointers(&mut self, inode: u32, block: u32, what: &str) -> Vec<(usize, u32)> {
+        if !self.claim(inode, block, what) {
+            return Vec::new();
+        }
+        let data = self.block(block);
+        (0..PTRS_PER_BLOCK)
+            .map(|i| (i, u32_at(data, i * 4)))
+            .filter(|&(_, ptr)| ptr != 0)
+            .collect()
+    }
+
+    fn check_blocks(&mut self) {
+        for num in 0..self.sb.inode_count {
+            let inode = self.inodes[num as usize];
+            if !in_use(&inode) {
+                continue;
+            }
+
+            // (logical block, physical block) pairs of the file data
+            let mut data = Vec::new();
+            let mut pointer_blocks = 0u32;
+            for (i, &b) in inode.direct_blocks.iter().enumerate() {
+                if b != 0 {
+                    data.push((i, b));
+                }
+            }
+            if inode.indirect_block != 0 {
+                pointer_blocks += 1;
+                for (i, b) in self.pointers(num, inode.indirect_block, "indirect") {
+                    data.push((DIRECT_BLOCKS + i, b));
+                }
+            }
+            if inode.double_indirect_block != 0 {
+                pointer_blocks += 1;
+                for (i, l1) in self.pointers(num, inode.double_indirect_block, "double indirect") {
+                    pointer_blocks += 1;
+                    for (j, b) in self.pointers(num, l1, "indirect") {
+                        data.push((DIRECT_BLOCKS + PTRS_PER_BLOCK * (1 + i) + j, b));
+                    }
+                }
+            }
+
+            let size_blocks = (inode.size as usize).div_ceil(BLOCK_SIZE);
+            let mut map = Vec::new();
+            for &(logical, b) in &data {
+                if !self.claim(num, b, "data") {
+                    continue;
+                }
+                if logical >= size_blocks {
+                    self.problems.push(format!(
+                        "inode {}: block {} mapped past the {} byte size",
+                        num, logical, inode.size
+                    ));
+                } else if inode.size <= MAX_VERIFIED_FILE {
+                    map.resize(map.len().max(logical + 1), 0);
+                    map[logical] = b;
+                }
+            }
+            self.maps.insert(num, map);
+
+            if inode.blocks != data.len() as u32 + pointer_blocks {
+                self.problems.push(format!(
+                    "inode {}: block count {}, but {} blocks are mapped",
+                    num,
+                    inode.blocks,
+                    data.len() as u32 + pointer_blocks
+                ));
+            }
+
+            if inode.xattr_block != 0 && self.claim(num, inode.xattr_block, "xattr") {
+                if let Err(e) = check_xattr_block(self.block(inode.xattr_block)) {
+                    self.problems.push(format!("inode {}: {}", num, e));
+                }
+            }
+        }
+
+        let mut free_blocks = 0;
+        for block in 0..self.sb.block_count {
+            let allocated = self.allocated(block);
+            if !allocated {
+                free_blocks += 1;
+            }
+            if block < self.sb.first_data_block {
+                if !allocated {
+                    self.problems
+                        .push(format!("metadata block {} is free in the bitmap", block));
+                }
+            } else if allocated && !self.owner.contains_key(&block) {
+                self.problems
+                    .push(format!("block {} is allocated but unused", block));
+            }
+        }
+        if free_blocks != self.sb.free_blocks {
+            self.problems.push(format!(
+                "superblock says {} free blocks, bitmap has {}",
+                self.sb.free_blocks, free_blocks
+            ));
+        }
+        let free_inodes = self.inodes.iter().filter(|i| !in_use(i)).count() as u32;
+        if free_inodes != self.sb.free_inodes {
+            self.problems.push(format!(
+                "superblock says {} free inodes, table has {}",
+                self.sb.free_inodes, free_inodes
+            ));
+        }
+    }
+
+    /// Contents of an inode, holes read as zeros.
+    fn read(&self, num: u32) -> Vec<u8> {
+        let size = self.inodes[num as usize].size as usize;
+        let mut out = vec![0u8; size];
+        for (logical, &b) in self.maps[&num].iter().enumerate() {
+            if b == 0 {
+                continue;
+            }
+            let start = logical * BLOCK_SIZE;
+            let len = BLOCK_SIZE.min(size - start);
+            out[start..start + len].copy_from_slice(&self.block(b)[..len]);
+        }
+        out
+    }
+
+    /// Parse a directory into (name, inode, file type) entries.
+    fn dir_entries(&mut self, num: u32) -> Vec<(String, u32, u8)> {
+        let inode = &self.inodes[num as usize];
+        if inode.indirect_block != 0 || inode.size as usize > DIRECT_BLOCKS * BLOCK_SIZE {
+            self.problems.push(format!(
+                "directory {} is larger than the kernel can read",
+                num
+            ));
+        }
+        if inode.size > MAX_VERIFIED_FILE {
+            return Vec::new();
+        }
+        let data = self.read(num);
+        let mut entries = Vec::new();
+        for (index, block) in data.chunks(BLOCK_SIZE).enumerate() {
+            let mut off = 0;
+            while let Some(header) = DirEntryHeader::decode(block, off) {
+                let rec_len = header.rec_len as usize;
+                let name_len = header.name_len as usize;
+                if !header.rec_len_ok() {
+                    // The kernel stops at the first bad record; anything but
+                    // zero padding after it is lost.
+                    if block[off..].iter().any(|&b| b != 0) {
+                        self.problems.push(format!(
+                            "directory {} block {}: bad record length {} at {}",
+                            num, index, rec_len, off
+                        ));
+                    }
+                    break;
+                }
+                if off + rec_len > block.len() {
+                    self.problems.push(format!(
+                        "directory {} block {}: entry at {} crosses the block end",
+                        num, index, off
+                    ));
+                    break;
+                }
+                if DIR_ENTRY_HEADER_SIZE + name_len > rec_len {
+                    self.problems.push(format!(
+                        "directory {} block {}: name of entry at {} overruns it",
+                        num, index, off
+                    ));
+                } else if header.inode != 0 || name_len > 0 {
+                    match std::str::from_utf8(header.name(block, off)) {
+                        Ok(name) => {
+                            entries.push((name.to_string(), header.inode, header.file_type))
+                        }
+                        Err(_) => self.problems.push(format!(
+                            "directory {} block {}: entry at {} has a non-UTF-8 name",
+                            num, index, off
+                        )),
+                    }
+                }
+                off += rec_len;
+            }
+        }
+        entries
+    }
+
+    fn check_tree(&mut self) -> Tree {
+        let root = &self.inodes[ROOT_INODE as usize];
+        if !has_type(root, S_IFDIR) {
+            self.problems
+                .push(format!("root inode has mode {:#o}", root.mode));
+            return Tree::new();
+        }
+
+        let mut references = vec![0u32; self.inodes.len()];
+        let mut subdirs = vec![0u32; self.inodes.len()];
+        let mut visited = BTreeSet::new();
+        visited.insert(ROOT_INODE);
+        let tree = self.walk(
+            ROOT_INODE,
+            ROOT_INODE,
+            &mut references,
+            &mut subdirs,
+            &mut visited,
+        );
+
+        for (num, inode) in self.inodes.iter().enumerate() {
+            if !in_use(inode) {
+                continue;
+            }
+            let expected = if has_type(inode, S_IFDIR) {
+                2 + subdirs[num]
+            } else {
+                references[num]
+            };
+            if num as u32 != ROOT_INODE && references[num] == 0 {
+                self.problems.push(format!("inode {} is orphaned", num));
+            } else if inode.links_count as u32 != expected {
+                self.problems.push(format!(
+                    "inode {}: link count {}, expected {}",
+                    num, inode.links_count, expected
+                ));
+            }
+        }
+        tree
+    }
+
+    fn walk(
+        &mut self,
+        dir: u32,
+        parent: u32,
+        references: &mut [u32],
+        subdirs: &mut [u32],
+        visited: &mut BTreeSet<u32>,
+    ) -> Tree {
+        let entries = self.dir_entries(dir);
+        let mut tree = Tree::new();
+        let mut names = entries.iter().map(|(name, ..)| name.as_str());
+        if names.next() != Some(".") || names.next() != Some("..") {
+            self.problems
+                .push(format!("directory {} does not start with . and ..", dir));
+        }
+
+        for (name, child, file_type) in entries {
+            match name.as_str() {
+                "." | ".." => {
+                    let target = if name == "." { dir } else { parent };
+                    if child != target {
+                        self.problems.push(format!(
+                            "directory {}: {} points to {}, not {}",
+                            dir, name, child, target
+                        ));
+                    }
+                    continue;
+                }
+                // Deleted slot; the kernel skips these.
+                _ if child == 0 => continue,
+                "" => {
+                    self.problems
+                        .push(format!("directory {}: entry with an empty name", dir));
+                    continue;
+                }
+                _ if name.contains('/') => {
+                    self.problems
+                        .push(format!("directory {}: name {:?} contains '/'", dir, name));
+                    continue;
+                }
+                _ if tree.contains_key(&name) => {
+                    self.problems
+                        .push(format!("directory {}: duplicate name {:?}", dir, name));
+                    continue;
+                }
+                _ => {}
+            }
+            let Some(inode) = self.inodes.get(child as usize).copied() else {
+                self.problems.push(format!(
+                    "directory {}: {:?} points to inode {} of {}",
+                    dir,
+                    name,
+                    child,
+                    self.inodes.len()
+                ));
+                continue;
+            };
+            let type_ok = match file_type {
+                FT_DIR => has_type(&inode, S_IFDIR),
+                FT_REG_FILE => has_type(&inode, S_IFREG),
+                FT_SYMLINK => has_type(&inode, S_IFLNK),
+                _ => false,
+            };
+            if !in_use(&inode) || !type_ok {
+                self.problems.push(format!(
+                    "directory {}: {:?} has type {} but inode {} has mode {:#o}",
+                    dir, name, file_type, child, inode.mode
+                ));
+                continue;
+            }
+            references[child as usize] += 1;
+
+            if has_type(&inode, S_IFDIR) {
+                subdirs[dir as usize] += 1;
+                if !visited.insert(child) {
+                    self.problems
+                        .push(format!("directory {} is linked more than once", child));
+                    continue;
+                }
+                let sub = self.walk(child, dir, references, subdirs, visited);
+                tree.insert(name, Entry::Dir(sub));
+            } else if inode.size <= MAX_VERIFIED_FILE {
+                tree.insert(name, Entry::File(self.read(child)));
+            }
+        }
+        tree
+    }
+}
+
+fn check_xattr_block(block: &[u8]) -> Result<(), String> {
+    let entries = decode_xattr_block(block).map_err(|e| format!("xattr block: {}", e))?;
+    for entry in entries {
+        let (name, _) = entry.map_err(|e| format!("xattr block: {}", e))?;
+        if name.is_empty() {
+            return Err("xattr block: entry with an empty name".to_string());
+        }
+    }
+    Ok(())
+}
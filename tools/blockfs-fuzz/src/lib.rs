@@ -0,0 +1,406 @@
+//! blockfs-fuzz -- Property and fuzz testing for the BlockFS on-disk format
+//!
+//! Host-side harness for the code that reads and writes BlockFS images:
+//!
+//! - **Operation sequences**: a seeded generator produces random trees of files
+//!   (with holes, indirect and double-indirect sizes) and directories, builds
+//!   them with `mkfs_blockfs` in either on-disk format version, and checks that
+//!   the image passes every invariant in [`check`] and holds exactly the
+//!   generated tree.
+//! - **Mutated images**: valid images get bit flips, byte overwrites and
+//!   interesting values, mostly in the metadata area. The checker must never
+//!   panic on them, and targeted damage that breaks an invariant (a cleared
+//!   bitmap bit, a block shared by two inodes, a torn directory record) must be
+//!   reported.
+//!
+//! Every case is derived from a `u64` seed, so a failure printed by the
+//! `blockfs-fuzz` binary or a test reproduces with `--seed`.
+
+pub mod check;
+
+use std::collections::BTreeMap;
+
+use mkfs_blockfs::{
+    computed_first_data_block, default_inode_count, BlockFsBuilder, InodeFormat, BLOCK_SIZE,
+    ROOT_INODE,
+};
+
+/// A node of a filesystem tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    File(Vec<u8>),
+    Dir(Tree),
+}
+
+/// Directory contents by name
+pub type Tree = BTreeMap<String, Entry>;
+
+/// Seeded PRNG (xorshift64), the same generator the kernel fuzzer uses
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 { 1 } else { seed },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Uniform value in `0..max` (0 if `max` is 0).
+    pub fn below(&mut self, max: usize) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        (self.next_u64() % max as u64) as usize
+    }
+
+    /// True with probability `1 / n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// One step of a generated workload
+#[derive(Debug, Clone)]
+pub enum Op {
+    /// Create a directory under the `parent`-th directory created so far
+    Mkdir { parent: usize, name: String },
+    /// Create a file under the `parent`-th directory created so far
+    Create {
+        parent: usize,
+        name: String,
+        data: Vec<u8>,
+    },
+}
+
+/// A generated case: image geometry plus the operations to apply
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub block_count: u32,
+    pub inode_count: u32,
+    pub format: InodeFormat,
+    pub ops: Vec<Op>,
+}
+
+/// Sizes that hit the interesting boundaries of the block map.
+const FILE_SIZES: &[usize] = &[
+    0,
+    1,
+    BLOCK_SIZE - 1,
+    BLOCK_SIZE,
+    BLOCK_SIZE + 1,
+    12 * BLOCK_SIZE,
+    13 * BLOCK_SIZE,
+    (12 + 1024) * BLOCK_SIZE,
+    (12 + 1024) * BLOCK_SIZE + 1,
+];
+
+fn file_data(rng: &mut Rng) -> Vec<u8> {
+    let size = if rng.one_in(3) {
+        FILE_SIZES[rng.below(FILE_SIZES.len())]
+    } else {
+        rng.below(6 * BLOCK_SIZE)
+    };
+    let mut data = vec![0u8; size];
+    // Fill some blocks and leave others as holes
+    for chunk in data.chunks_mut(BLOCK_SIZE) {
+        if !rng.one_in(4) {
+            let fill = rng.next_u64() as u8 | 1;
+            let start = rng.below(chunk.len());
+            for (i, b) in chunk[start..].iter_mut().enumerate() {
+                *b = fill.wrapping_add(i as u8);
+            }
+        }
+    }
+    data
+}
+
+fn name(rng: &mut Rng, taken: &Tree) -> String {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789._-";
+    loop {
+        // Mostly short names, sometimes up to the 255-byte limit
+        let len = if rng.one_in(8) {
+            1 + rng.below(255)
+        } else {
+            1 + rng.below(16)
+        };
+        let name: String = (0..len)
+            .map(|_| CHARS[rng.below(CHARS.len())] as char)
+            .collect();
+        if name != "." && name != ".." && !taken.contains_key(&name) {
+            return name;
+        }
+    }
+}
+
+/// Directory `path` of `tree`, by child names from the root.
+fn dir_at<'a>(tree: &'a mut Tree, path: &[String]) -> &'a mut Tree {
+    path.iter().fold(tree, |dir, part| match dir.get_mut(part) {
+        Some(Entry::Dir(sub)) => sub,
+        _ => unreachable!("generated paths are directories"),
+    })
+}
+
+impl Case {
+    /// Generate a case from `seed`, with at most `max_ops` operations.
+    ///
+    /// The image is sized from the generated data so the builder never runs
+    /// out of blocks or inodes.
+    pub fn generate(seed: u64, max_ops: usize) -> (Self, Tree) {
+        let mut rng = Rng::new(seed);
+        let mut model = Tree::new();
+        let mut dirs: Vec<Vec<String>> = vec![Vec::new()];
+        let mut ops = Vec::new();
+        let mut data_blocks = 0usize;
+
+        for _ in 0..1 + rng.below(max_ops) {
+            // Favour the root so some directories grow past one block
+            let parent = if rng.one_in(2) {
+                0
+            } else {
+                rng.below(dirs.len())
+            };
+            let dir = dir_at(&mut model, &dirs[parent]);
+            let name = name(&mut rng, dir);
+            if rng.one_in(3) {
+                dir.insert(name.clone(), Entry::Dir(Tree::new()));
+                let mut path = dirs[parent].clone();
+                path.push(name.clone());
+                dirs.push(path);
+                ops.push(Op::Mkdir { parent, name });
+                data_blocks += 1;
+            } else {
+                let data = file_data(&mut rng);
+                // Data, up to three pointer blocks, and directory growth
+                data_blocks += data.len().div_ceil(BLOCK_SIZE) + 4;
+                dir.insert(name.clone(), Entry::File(data.clone()));
+                ops.push(Op::Create { parent, name, data });
+            }
+        }
+
+        // Room for every directory entry and slack the allocator may skip
+        data_blocks += ops.len() * (8 + 255) / BLOCK_SIZE + dirs.len() + 16;
+        let inode_count = default_inode_count(0).max(ops.len() as u32 + 1);
+        let format = if rng.one_in(4) {
+            InodeFormat::V1
+        } else {
+            InodeFormat::V2
+        };
+        let mut block_count = data_blocks as u32 + 64;
+        block_count += computed_first_data_block(block_count, inode_count, format);
+
+        let case = Self {
+            block_count,
+            inode_count,
+            format,
+            ops,
+        };
+        (case, model)
+    }
+
+    /// Build the case with `mkfs_blockfs` and return the raw image.
+    pub fn build(&self) -> Vec<u8> {
+        let mut builder =
+            BlockFsBuilder::with_format(self.block_count, self.inode_count, self.format);
+        let mut dirs = vec![ROOT_INODE];
+        for op in &self.ops {
+            match op {
+                Op::Mkdir { parent, name } => {
+                    let inode = builder.create_directory(dirs[*parent], name);
+                    dirs.push(inode);
+                }
+                Op::Create { parent, name, data } => {
+                    builder.create_file(dirs[*parent], name, data, 0x81A4);
+                }
+            }
+        }
+        builder.to_image()
+    }
+}
+
+/// Build the case for `seed` and check it against its model.
+pub fn run_ops(seed: u64, max_ops: usize) -> Result<(), String> {
+    let (case, model) = Case::generate(seed, max_ops);
+    let image = case.build();
+    match check::check(&image) {
+        Ok(tree) if tree == model => Ok(()),
+        Ok(_) => Err(format!(
+            "seed {:#x}: image does not hold the generated tree",
+            seed
+        )),
+        Err(problems) => Err(format!("seed {:#x}: {}", seed, problems.join("; "))),
+    }
+}
+
+/// Values that tend to hit edge cases in length and index fields.
+const INTERESTING: &[u32] = &[
+    0,
+    1,
+    3,
+    4,
+    7,
+    8,
+    0xFF,
+    0xFFF,
+    0x1000,
+    0xFFFF,
+    0x7FFF_FFFF,
+    u32::MAX,
+];
+
+/// Corrupt `image` in place with `count` random mutations.
+///
+/// Mutations favour the metadata area (superblock, bitmap, inode table)
+/// and the first data blocks, where directories live.
+pub fn mutate(image: &mut [u8], rng: &mut Rng, count: usize) {
+    let hot = image.len().min(BLOCK_SIZE * 64);
+    for _ in 0..count {
+        let range = if rng.one_in(4) { image.len() } else { hot };
+        let pos = rng.below(range);
+        match rng.below(3) {
+            0 => image[pos] ^= 1 << rng.below(8),
+            1 => image[pos] = rng.next_u64() as u8,
+            _ => {
+                let value = INTERESTING[rng.below(INTERESTING.len())].to_le_bytes();
+                let pos = pos.min(image.len() - 4) & !3;
+                image[pos..pos + 4].copy_from_slice(&value);
+            }
+        }
+    }
+}
+
+/// Mutate the image for `seed` and make sure the checker survives it.
+///
+/// Returns whether the checker still accepted the image.
+pub fn run_mutation(seed: u64, max_ops: usize) -> bool {
+    let (case, _) = Case::generate(seed, max_ops);
+    let mut image = case.build();
+    let mut rng = Rng::new(seed ^ 0x9E37_79B9_7F4A_7C15);
+    let count = 1 + rng.below(16);
+    mutate(&mut image, &mut rng, count);
+    check::check(&image).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(image: &[u8], off: usize) -> u32 {
+        u32::from_le_bytes(image[off..off + 4].try_into().unwrap())
+    }
+
+    /// Byte offset of inode `num` in a v2 `image`.
+    fn inode_offset(image: &[u8], num: u32) -> usize {
+        let (block, off) = blockfs_format::inode_location(u32_at(image, 4), num, InodeFormat::V2);
+        block as usize * BLOCK_SIZE + off
+    }
+
+    /// First direct block of inode `num`.
+    fn first_block(image: &[u8], num: u32) -> u32 {
+        u32_at(image, inode_offset(image, num) + 64)
+    }
+
+    fn build_sample(format: InodeFormat) -> Vec<u8> {
+        let mut builder = BlockFsBuilder::with_format(512, 672, format);
+        let dir = builder.create_directory(ROOT_INODE, "dir");
+        builder.create_file(dir, "a", &[7u8; 3 * BLOCK_SIZE], 0x81A4);
+        builder.create_file(ROOT_INODE, "b", b"hello", 0x81A4);
+        builder.to_image()
+    }
+
+    fn sample() -> Vec<u8> {
+        build_sample(InodeFormat::V2)
+    }
+
+    #[test]
+    fn test_random_op_sequences() {
+        for seed in 1..=64 {
+            run_ops(seed, 24).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_large_directories() {
+        // Enough entries to spill directories over several blocks
+        for seed in 100..104 {
+            run_ops(seed, 400).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_mutated_images_never_panic() {
+        for seed in 1..=256 {
+            run_mutation(seed, 12);
+        }
+    }
+
+    #[test]
+    fn test_sample_tree() {
+        let tree = check::check(&sample()).unwrap();
+        let Some(Entry::Dir(dir)) = tree.get("dir") else {
+            panic!("dir missing");
+        };
+        assert_eq!(dir.get("a"), Some(&Entry::File(vec![7u8; 3 * BLOCK_SIZE])));
+        assert_eq!(tree.get("b"), Some(&Entry::File(b"hello".to_vec())));
+    }
+
+    #[test]
+    fn test_v1_image() {
+        let image = build_sample(InodeFormat::V1);
+        assert_eq!(check::check(&image), check::check(&sample()));
+
+        // Claiming an unknown incompatible feature makes the image unreadable.
+        let mut image = sample();
+        image[72..76].copy_from_slice(&(1u32 << 31 | 1).to_le_bytes());
+        let problems = check::check(&image).unwrap_err();
+        assert!(problems.iter().any(|p| p.contains("unsupported")));
+    }
+
+    #[test]
+    fn test_detects_cleared_bitmap_bit() {
+        let mut image = sample();
+        let block = first_block(&image, 2) as usize;
+        image[BLOCK_SIZE + block / 8] &= !(1 << (block % 8));
+        let problems = check::check(&image).unwrap_err();
+        assert!(problems.iter().any(|p| p.contains("free in the bitmap")));
+    }
+
+    #[test]
+    fn test_detects_double_allocation() {
+        let mut image = sample();
+        let shared = first_block(&image, 2);
+        let off = inode_offset(&image, 3) + 64;
+        image[off..off + 4].copy_from_slice(&shared.to_le_bytes());
+        let problems = check::check(&image).unwrap_err();
+        assert!(problems.iter().any(|p| p.contains("allocated twice")));
+    }
+
+    #[test]
+    fn test_detects_torn_directory_record() {
+        let mut image = sample();
+        let dir_block = first_block(&image, 1) as usize * BLOCK_SIZE;
+        // rec_len of "." made to run off the end of the block
+        image[dir_block + 4..dir_block + 6].copy_from_slice(&0x2000u16.to_le_bytes());
+        let problems = check::check(&image).unwrap_err();
+        assert!(problems.iter().any(|p| p.contains("crosses the block end")));
+    }
+
+    #[test]
+    fn test_detects_link_count_mismatch() {
+        let mut image = sample();
+        let off = inode_offset(&image, 3) + 6;
+        image[off..off + 2].copy_from_slice(&2u16.to_le_bytes());
+        let problems = check::check(&image).unwrap_err();
+        assert!(problems.iter().any(|p| p.contains("link count")));
+    }
+}
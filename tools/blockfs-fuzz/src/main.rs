@@ -0,0 +1,85 @@
+//! blockfs-fuzz -- Long-running BlockFS property and mutation fuzzing
+//!
+//! Usage:
+//!   blockfs-fuzz [--seed <n>] [--iterations <n>] [--max-ops <n>]
+//!
+//! Each iteration checks one generated operation sequence and one mutated
+//! image. The first failing seed is printed; rerun with `--seed <seed>
+//! --iterations 1` to reproduce it.
+
+use std::{env, process};
+
+use blockfs_fuzz::{run_mutation, run_ops};
+
+fn print_usage() {
+    eprintln!("Usage: blockfs-fuzz [--seed <n>] [--iterations <n>] [--max-ops <n>]");
+}
+
+fn parse(value: Option<&String>) -> u64 {
+    let Some(value) = value else {
+        print_usage();
+        process::exit(1);
+    };
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.unwrap_or_else(|_| {
+        eprintln!("Invalid number: {}", value);
+        process::exit(1);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut seed = 1u64;
+    let mut iterations = 10_000u64;
+    let mut max_ops = 32usize;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--seed" => {
+                i += 1;
+                seed = parse(args.get(i));
+            }
+            "--iterations" | "-n" => {
+                i += 1;
+                iterations = parse(args.get(i));
+            }
+            "--max-ops" => {
+                i += 1;
+                max_ops = parse(args.get(i)) as usize;
+            }
+            "--help" | "-h" => {
+                print_usage();
+                return;
+            }
+            _ => {
+                eprintln!("Unknown option: {}", args[i]);
+                print_usage();
+                process::exit(1);
+            }
+        }
+        i += 1;
+    }
+
+    let mut accepted = 0u64;
+    for n in 0..iterations {
+        let case_seed = seed.wrapping_add(n);
+        if let Err(e) = run_ops(case_seed, max_ops) {
+            eprintln!("FAIL {}", e);
+            process::exit(1);
+        }
+        if run_mutation(case_seed, max_ops) {
+            accepted += 1;
+        }
+        if (n + 1) % 1000 == 0 {
+            println!("{} iterations", n + 1);
+        }
+    }
+    println!(
+        "blockfs-fuzz: {} cases passed, {} of {} mutated images still valid",
+        iterations, accepted, iterations
+    );
+}
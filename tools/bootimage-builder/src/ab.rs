@@ -0,0 +1,141 @@
+//! A/B boot disk layout
+//!
+//! Turns the single-partition image produced by the bootloader crate into a
+//! GPT disk with two kernel slots and a boot-selection record:
+//!
+//! | Partition           | Contents                                         |
+//! |---------------------|--------------------------------------------------|
+//! | EFI system          | bootloader + kernel file (the boot *window*)     |
+//! | `veridian-kernel-a` | kernel image, slot A                             |
+//! | `veridian-kernel-b` | kernel image, slot B                             |
+//! | `veridian-bootsel`  | boot-selection record (two copies)               |
+//! | `veridian-rootfs`   | optional root filesystem (TAR or BlockFS)        |
+//! | `veridian-crash`    | kernel crash dump, empty                         |
+//!
+//! The bootloader always loads the kernel file on the ESP, so the kernel
+//! switches slots by copying a slot image over that file. The file is
+//! padded to the slot size so any slot image fits, and must be stored
+//! contiguously; its sector range is recorded in the boot-selection record.
+//!
+//! The record format is defined in `kernel/src/bootslot.rs`.
+
+use anyhow::{bail, Result};
+
+use crate::gpt::{
+    self, crc32, first_partition, Partition, ALIGN, ESP_PARTITION, ESP_TYPE, ROOTFS_PARTITION,
+    ROOTFS_TYPE, SECTOR,
+};
+
+const KERNEL_SLOT_TYPE: &str = "5645524B-534C-4F54-8000-564552494449";
+const BOOTSEL_TYPE: &str = "56455242-5345-4C00-8000-564552494449";
+const CRASH_TYPE: &str = "56455243-5241-5348-8000-564552494449";
+
+const KERNEL_PARTITIONS: [&str; 2] = ["veridian-kernel-a", "veridian-kernel-b"];
+const BOOTSEL_PARTITION: &str = "veridian-bootsel";
+/// Written by `kernel/src/crash` when the kernel panics.
+const CRASH_PARTITION: &str = "veridian-crash";
+
+const RECORD_MAGIC: &[u8; 4] = b"VBSR";
+const RECORD_VERSION: u16 = 1;
+const RECORD_LEN: usize = 60;
+const SLOT_GOOD: u8 = 1;
+
+/// Slot size for a kernel: room for it to double, rounded to 1 MiB.
+pub fn default_slot_size(kernel_len: usize) -> u64 {
+    (kernel_len as u64 * 2).div_ceil(1 << 20) << 20
+}
+
+/// `kernel` zero-padded to `slot_size` bytes: the ESP kernel file.
+pub fn pad_kernel(kernel: &[u8], slot_size: u64) -> Result<Vec<u8>> {
+    if kernel.len() as u64 > slot_size || slot_size % SECTOR as u64 != 0 {
+        bail!(
+            "slot size {} is too small for the {} byte kernel or not a multiple of {}",
+            slot_size,
+            kernel.len(),
+            SECTOR
+        );
+    }
+    let mut padded = kernel.to_vec();
+    padded.resize(slot_size as usize, 0);
+    Ok(padded)
+}
+
+/// Sector offset of the padded kernel file inside the ESP.
+fn find_window(esp: &[u8], padded: &[u8]) -> Result<u64> {
+    let head = &padded[..SECTOR];
+    for off in (0..esp.len().saturating_sub(padded.len()) + 1).step_by(SECTOR) {
+        if &esp[off..off + SECTOR] == head && &esp[off..off + padded.len()] == padded {
+            return Ok((off / SECTOR) as u64);
+        }
+    }
+    bail!("kernel file is not stored contiguously in the ESP")
+}
+
+/// Boot-selection record: slot A active, both slots holding `kernel`.
+fn boot_record(kernel: &[u8], window_lba: u64, window_sectors: u32) -> [u8; SECTOR] {
+    let mut r = [0u8; SECTOR];
+    r[0..4].copy_from_slice(RECORD_MAGIC);
+    r[4..6].copy_from_slice(&RECORD_VERSION.to_le_bytes());
+    r[8..12].copy_from_slice(&1u32.to_le_bytes());
+    r[12..16].copy_from_slice(&window_sectors.to_le_bytes());
+    r[16..24].copy_from_slice(&window_lba.to_le_bytes());
+    for slot in 0..2 {
+        let e = &mut r[24 + slot * 16..40 + slot * 16];
+        e[0] = SLOT_GOOD;
+        e[4..8].copy_from_slice(&(kernel.len() as u32).to_le_bytes());
+        e[8..12].copy_from_slice(&crc32(kernel).to_le_bytes());
+        e[12..16].copy_from_slice(&1u32.to_le_bytes());
+    }
+    let crc = crc32(&r[..RECORD_LEN - 4]);
+    r[RECORD_LEN - 4..RECORD_LEN].copy_from_slice(&crc.to_le_bytes());
+    r
+}
+
+/// Build the A/B disk from `stock`, the bootloader's disk image for the
+/// kernel padded to `slot_size` bytes.
+pub fn build(
+    stock: &[u8],
+    kernel: &[u8],
+    slot_size: u64,
+    rootfs: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let padded = pad_kernel(kernel, slot_size)?;
+    let esp = &stock[first_partition(stock)?];
+    let window = find_window(esp, &padded)?;
+    let slot_sectors = slot_size / SECTOR as u64;
+
+    let slot = |name| Partition {
+        name,
+        type_guid: KERNEL_SLOT_TYPE,
+        sectors: slot_sectors,
+        data: kernel,
+    };
+    let mut parts = vec![
+        Partition::holding(ESP_PARTITION, ESP_TYPE, esp),
+        slot(KERNEL_PARTITIONS[0]),
+        slot(KERNEL_PARTITIONS[1]),
+        Partition {
+            name: BOOTSEL_PARTITION,
+            type_guid: BOOTSEL_TYPE,
+            sectors: ALIGN,
+            data: &[],
+        },
+    ];
+    if let Some(rootfs) = rootfs {
+        parts.push(Partition::holding(ROOTFS_PARTITION, ROOTFS_TYPE, rootfs));
+    }
+    parts.push(Partition {
+        name: CRASH_PARTITION,
+        type_guid: CRASH_TYPE,
+        sectors: ALIGN,
+        data: &[],
+    });
+    let (mut disk, first_lbas) = gpt::build(&parts, crc32(kernel))?;
+
+    // The record; sequence number 1 goes in copy 1
+    let record = boot_record(kernel, first_lbas[0] + window, slot_sectors as u32);
+    let at = (first_lbas[3] + 1) as usize * SECTOR;
+    disk[at..at + SECTOR].copy_from_slice(&record);
+
+    Ok(disk)
+}
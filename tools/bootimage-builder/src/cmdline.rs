@@ -0,0 +1,89 @@
+//! Kernel command line embedding
+//!
+//! The x86_64 boot protocol carries no command line, so the kernel reserves
+//! a `.cmdline` section (`kernel/src/utils/cmdline.rs` defines it) holding a
+//! magic, a length and the text. `--cmdline` writes the text into a copy of
+//! the kernel, in place, before the disk images are built.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+const CMDLINE_SECTION: &str = ".cmdline";
+const CMDLINE_MAGIC: &[u8; 4] = b"VCMD";
+const HEADER_LEN: usize = 8;
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes(buf[off..off + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(buf[off..off + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(buf[off..off + 8].try_into().unwrap())
+}
+
+/// File offset and size of section `name` of an ELF64 image.
+fn find_section(image: &[u8], name: &str) -> Result<Option<(usize, usize)>> {
+    if image.len() < 64 || &image[..4] != b"\x7fELF" || image[4] != 2 || image[5] != 1 {
+        bail!("kernel is not a little-endian ELF64 file");
+    }
+    let shoff = read_u64(image, 0x28) as usize;
+    let shentsize = read_u16(image, 0x3A) as usize;
+    let shnum = read_u16(image, 0x3C) as usize;
+    let shstrndx = read_u16(image, 0x3E) as usize;
+    let header = |index: usize| shoff + index * shentsize;
+    if header(shnum) > image.len() || shstrndx >= shnum {
+        bail!("kernel section headers out of range");
+    }
+
+    let strtab = read_u64(image, header(shstrndx) + 0x18) as usize;
+    Ok((0..shnum).find_map(|index| {
+        let h = header(index);
+        let start = strtab + read_u32(image, h) as usize;
+        let end = start + image.get(start..)?.iter().position(|&b| b == 0)?;
+        (&image[start..end] == name.as_bytes()).then(|| {
+            (
+                read_u64(image, h + 0x18) as usize,
+                read_u64(image, h + 0x20) as usize,
+            )
+        })
+    }))
+}
+
+/// Write `cmdline` into the `.cmdline` section of `image`.
+fn embed(image: &mut [u8], cmdline: &str) -> Result<()> {
+    let (offset, size) = find_section(image, CMDLINE_SECTION)?
+        .context("kernel has no .cmdline section (built before command line support?)")?;
+    let area = image
+        .get_mut(offset..offset + size)
+        .context(".cmdline section out of range")?;
+    if size < HEADER_LEN || &area[..4] != CMDLINE_MAGIC {
+        bail!(".cmdline section does not hold a command line");
+    }
+    let text = cmdline.trim().as_bytes();
+    if text.len() > size - HEADER_LEN {
+        bail!(
+            "command line is {} bytes; the kernel has room for {}",
+            text.len(),
+            size - HEADER_LEN
+        );
+    }
+    area[4..8].copy_from_slice(&(text.len() as u32).to_le_bytes());
+    area[HEADER_LEN..].fill(0);
+    area[HEADER_LEN..HEADER_LEN + text.len()].copy_from_slice(text);
+    Ok(())
+}
+
+/// Copy the kernel at `kernel` to `veridian-kernel.cmdline` in `output_dir`
+/// with `cmdline` embedded, and return the copy's path.
+pub fn patch_kernel(kernel: &Path, cmdline: &str, output_dir: &Path) -> Result<PathBuf> {
+    let mut image = std::fs::read(kernel).context("Failed to read kernel")?;
+    embed(&mut image, cmdline)?;
+    let patched = output_dir.join("veridian-kernel.cmdline");
+    std::fs::write(&patched, &image).context("Failed to write kernel")?;
+    println!("Embedded command line: {}", cmdline.trim());
+    Ok(patched)
+}
@@ -0,0 +1,94 @@
+//! Initramfs archive writer
+//!
+//! Packs a host directory into a "newc" cpio archive, the format unpacked by
+//! `kernel/src/fs/initramfs.rs`. Entries are written in sorted order with
+//! parents before children, owned by root, so images are reproducible.
+//! Hard links are stored as independent files.
+
+use std::{
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+
+const MAGIC: &str = "070701";
+const TRAILER: &str = "TRAILER!!!";
+
+fn pad4(out: &mut Vec<u8>) {
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+fn push_entry(out: &mut Vec<u8>, name: &str, ino: u32, mode: u32, mtime: u32, data: &[u8]) {
+    let nlink = if mode & 0o170000 == 0o040000 { 2 } else { 1 };
+    let fields = [
+        ino,
+        mode,
+        0, // uid
+        0, // gid
+        nlink,
+        mtime,
+        data.len() as u32,
+        0, // devmajor
+        0, // devminor
+        0, // rdevmajor
+        0, // rdevminor
+        name.len() as u32 + 1,
+        0, // check
+    ];
+    out.extend_from_slice(MAGIC.as_bytes());
+    for value in fields {
+        out.extend_from_slice(format!("{:08X}", value).as_bytes());
+    }
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    pad4(out);
+    out.extend_from_slice(data);
+    pad4(out);
+}
+
+fn pack_tree(out: &mut Vec<u8>, dir: &Path, prefix: &str, ino: &mut u32) -> Result<()> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .collect::<std::io::Result<_>>()?;
+    children.sort_by_key(|e| e.file_name());
+
+    for child in children {
+        let path = child.path();
+        let name = format!("{}/{}", prefix, child.file_name().to_string_lossy());
+        let meta = std::fs::symlink_metadata(&path)
+            .with_context(|| format!("Failed to stat {}", path.display()))?;
+        let mode = meta.permissions().mode();
+        let mtime = meta.mtime().clamp(0, u32::MAX as i64) as u32;
+        *ino += 1;
+
+        if meta.file_type().is_symlink() {
+            let target = std::fs::read_link(&path)?;
+            let target = target.to_string_lossy();
+            push_entry(out, &name, *ino, mode, mtime, target.as_bytes());
+        } else if meta.is_dir() {
+            push_entry(out, &name, *ino, mode, mtime, &[]);
+            pack_tree(out, &path, &name, ino)?;
+        } else if meta.is_file() {
+            let data = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            if data.len() > u32::MAX as usize {
+                anyhow::bail!("{} is too large for a cpio archive", path.display());
+            }
+            push_entry(out, &name, *ino, mode, mtime, &data);
+        } else {
+            println!("  Skipping special file {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Pack the directory tree at `root` into a newc cpio archive.
+pub fn pack_dir(root: &Path) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut ino = 1;
+    push_entry(&mut out, ".", ino, 0o040755, 0, &[]);
+    pack_tree(&mut out, root, ".", &mut ino)?;
+    push_entry(&mut out, TRAILER, 0, 0, 0, &[]);
+    Ok(out)
+}
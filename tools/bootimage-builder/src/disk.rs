@@ -0,0 +1,86 @@
+//! Combined boot + root disk
+//!
+//! `--rootfs-dir` produces `veridian-disk.img`: the bootloader's EFI system
+//! partition followed by a `veridian-rootfs` partition holding a BlockFS
+//! image of the directory, built with the `mkfs_blockfs` library. The kernel
+//! finds the partition by name and mounts it as the persistent root (see
+//! `load_rootfs_from_disk` in `kernel/src/bootstrap.rs`).
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use mkfs_blockfs::{default_inode_count, BlockFsBuilder, BLOCK_SIZE};
+
+use crate::gpt::{
+    self, crc32, first_partition, Partition, ESP_PARTITION, ESP_TYPE, ROOTFS_PARTITION, ROOTFS_TYPE,
+};
+
+const MIB: u64 = 1 << 20;
+/// Smallest root filesystem built by default.
+const MIN_ROOTFS_MIB: u64 = 32;
+/// Free space left on a default-sized root filesystem.
+const ROOTFS_HEADROOM_MIB: u64 = 16;
+
+/// Total size of the regular files under `dir`.
+fn tree_size(dir: &Path) -> Result<u64> {
+    let mut total = 0;
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let entry = entry?;
+        let meta = entry.path().symlink_metadata()?;
+        if meta.is_dir() {
+            total += tree_size(&entry.path())?;
+        } else if meta.is_file() {
+            total += meta.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Default root filesystem size: the tree plus a quarter for metadata and
+/// block rounding, plus headroom.
+fn default_size_mib(dir: &Path) -> Result<u64> {
+    let content = tree_size(dir)?;
+    Ok((content + content / 4).div_ceil(MIB).max(MIN_ROOTFS_MIB) + ROOTFS_HEADROOM_MIB)
+}
+
+/// Build a BlockFS image of `dir`, `size_mib` MiB large (default: sized to
+/// the tree).
+pub fn blockfs_image(dir: &Path, size_mib: Option<u64>) -> Result<Vec<u8>> {
+    if !dir.is_dir() {
+        bail!("Root filesystem directory not found: {}", dir.display());
+    }
+    let size_mib = match size_mib {
+        Some(size) => size,
+        None => default_size_mib(dir)?,
+    };
+    let block_count = u32::try_from(size_mib * MIB / BLOCK_SIZE as u64)
+        .context("root filesystem size is too large")?;
+
+    println!(
+        "Building BlockFS root filesystem from {} ({} MiB)...",
+        dir.display(),
+        size_mib
+    );
+    let mut builder = BlockFsBuilder::new(block_count, default_inode_count(block_count));
+    builder.populate_from_dir(dir, 0);
+    println!(
+        "  Inodes used: {}, free blocks: {}",
+        builder.inodes_used(),
+        builder.free_blocks_count()
+    );
+    Ok(builder.to_image())
+}
+
+/// Build the combined disk from `stock`, the bootloader's disk image, and a
+/// root filesystem image.
+pub fn build(stock: &[u8], rootfs: &[u8]) -> Result<Vec<u8>> {
+    let esp = &stock[first_partition(stock)?];
+    let parts = [
+        Partition::holding(ESP_PARTITION, ESP_TYPE, esp),
+        Partition::holding(ROOTFS_PARTITION, ROOTFS_TYPE, rootfs),
+    ];
+    let (disk, _) = gpt::build(&parts, crc32(esp))?;
+    Ok(disk)
+}
@@ -0,0 +1,207 @@
+//! GPT disk assembly
+//!
+//! Shared by the A/B boot disk (`ab.rs`) and the combined boot + root disk
+//! (`disk.rs`): lays partitions out on 1 MiB boundaries behind a protective
+//! MBR, with primary and backup GPT headers. GUIDs are derived from a seed
+//! so images are reproducible.
+
+use anyhow::{bail, Context, Result};
+
+pub const SECTOR: usize = 512;
+/// Partitions start on 1 MiB boundaries.
+pub const ALIGN: u64 = 2048;
+const GPT_ENTRIES: usize = 128;
+const GPT_ENTRY_SIZE: usize = 128;
+/// Sectors taken by the partition entry array.
+const GPT_ENTRY_SECTORS: u64 = (GPT_ENTRIES * GPT_ENTRY_SIZE / SECTOR) as u64;
+
+pub const ESP_TYPE: &str = "C12A7328-F81F-11D2-BA4B-00A0C93EC93B";
+/// Linux filesystem data; used for the root filesystem partition.
+pub const ROOTFS_TYPE: &str = "0FC63DAF-8483-4772-8E79-3D69D8477DE4";
+
+pub const ESP_PARTITION: &str = "EFI system partition";
+/// Found by name by the kernel (`kernel/src/bootslot.rs`).
+pub const ROOTFS_PARTITION: &str = "veridian-rootfs";
+
+/// CRC-32 (IEEE 802.3), as used by GPT and the boot-selection record.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn read_u64(buf: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(buf[off..off + 8].try_into().unwrap())
+}
+
+/// Byte range of the first partition of a GPT disk image.
+pub fn first_partition(image: &[u8]) -> Result<std::ops::Range<usize>> {
+    let header = image.get(SECTOR..2 * SECTOR).context("image too small")?;
+    if &header[0..8] != b"EFI PART" {
+        bail!("bootloader image has no GPT");
+    }
+    let entries = read_u64(header, 72) as usize * SECTOR;
+    let entry = image
+        .get(entries..entries + GPT_ENTRY_SIZE)
+        .context("GPT entries out of range")?;
+    let first = read_u64(entry, 32) as usize * SECTOR;
+    let last = read_u64(entry, 40) as usize * SECTOR;
+    if last < first || last + SECTOR > image.len() {
+        bail!("bad ESP partition entry");
+    }
+    Ok(first..last + SECTOR)
+}
+
+/// Encode a GUID string in GPT's mixed-endian layout.
+fn guid(text: &str) -> [u8; 16] {
+    let hex: String = text.chars().filter(|c| *c != '-').collect();
+    let mut raw = [0u8; 16];
+    for (i, byte) in raw.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+    }
+    raw[0..4].reverse();
+    raw[4..6].reverse();
+    raw[6..8].reverse();
+    raw
+}
+
+/// Deterministic unique GUID (version 4 layout) so images are reproducible.
+fn unique_guid(seed: u32, index: u32) -> [u8; 16] {
+    let mut raw = [0u8; 16];
+    for (i, chunk) in raw.chunks_mut(4).enumerate() {
+        let word = crc32(
+            &[
+                seed.to_le_bytes(),
+                index.to_le_bytes(),
+                (i as u32).to_le_bytes(),
+            ]
+            .concat(),
+        );
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    raw[7] = (raw[7] & 0x0F) | 0x40;
+    raw[8] = (raw[8] & 0x3F) | 0x80;
+    raw
+}
+
+/// A partition to lay out: `data` is copied to its start and the rest is
+/// zero-filled up to `sectors`.
+pub struct Partition<'a> {
+    pub name: &'a str,
+    pub type_guid: &'a str,
+    pub sectors: u64,
+    pub data: &'a [u8],
+}
+
+impl<'a> Partition<'a> {
+    /// A partition exactly large enough for `data`.
+    pub fn holding(name: &'a str, type_guid: &'a str, data: &'a [u8]) -> Self {
+        Self {
+            name,
+            type_guid,
+            sectors: (data.len() as u64).div_ceil(SECTOR as u64),
+            data,
+        }
+    }
+}
+
+fn gpt_entries(parts: &[Partition], first_lbas: &[u64], seed: u32) -> Vec<u8> {
+    let mut entries = vec![0u8; GPT_ENTRIES * GPT_ENTRY_SIZE];
+    for (i, (part, &first_lba)) in parts.iter().zip(first_lbas).enumerate() {
+        let e = &mut entries[i * GPT_ENTRY_SIZE..(i + 1) * GPT_ENTRY_SIZE];
+        e[0..16].copy_from_slice(&guid(part.type_guid));
+        e[16..32].copy_from_slice(&unique_guid(seed, i as u32 + 1));
+        e[32..40].copy_from_slice(&first_lba.to_le_bytes());
+        e[40..48].copy_from_slice(&(first_lba + part.sectors - 1).to_le_bytes());
+        for (j, unit) in part.name.encode_utf16().take(36).enumerate() {
+            e[56 + j * 2..58 + j * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+    }
+    entries
+}
+
+fn gpt_header(
+    my_lba: u64,
+    alt_lba: u64,
+    entries_lba: u64,
+    disk_sectors: u64,
+    entries_crc: u32,
+    seed: u32,
+) -> [u8; SECTOR] {
+    let mut h = [0u8; SECTOR];
+    h[0..8].copy_from_slice(b"EFI PART");
+    h[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+    h[12..16].copy_from_slice(&92u32.to_le_bytes());
+    h[24..32].copy_from_slice(&my_lba.to_le_bytes());
+    h[32..40].copy_from_slice(&alt_lba.to_le_bytes());
+    h[40..48].copy_from_slice(&(2 + GPT_ENTRY_SECTORS).to_le_bytes());
+    h[48..56].copy_from_slice(&(disk_sectors - 2 - GPT_ENTRY_SECTORS).to_le_bytes());
+    h[56..72].copy_from_slice(&unique_guid(seed, 0));
+    h[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+    h[80..84].copy_from_slice(&(GPT_ENTRIES as u32).to_le_bytes());
+    h[84..88].copy_from_slice(&(GPT_ENTRY_SIZE as u32).to_le_bytes());
+    h[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+    let crc = crc32(&h[..92]);
+    h[16..20].copy_from_slice(&crc.to_le_bytes());
+    h
+}
+
+fn align_up(lba: u64) -> u64 {
+    lba.div_ceil(ALIGN) * ALIGN
+}
+
+/// Build a GPT disk holding `parts` in order. Returns the disk and the first
+/// LBA of each partition.
+pub fn build(parts: &[Partition], seed: u32) -> Result<(Vec<u8>, Vec<u64>)> {
+    let mut first_lbas = Vec::with_capacity(parts.len());
+    let mut next = ALIGN;
+    for part in parts {
+        if part.sectors == 0 || part.data.len() as u64 > part.sectors * SECTOR as u64 {
+            bail!("partition {} does not fit its contents", part.name);
+        }
+        first_lbas.push(next);
+        next = align_up(next + part.sectors);
+    }
+    let disk_sectors = next + 1 + GPT_ENTRY_SECTORS;
+
+    let mut disk = vec![0u8; disk_sectors as usize * SECTOR];
+    let at = |lba: u64| lba as usize * SECTOR;
+
+    // Protective MBR
+    let mbr = &mut disk[..SECTOR];
+    mbr[446 + 1..446 + 4].copy_from_slice(&[0x00, 0x02, 0x00]);
+    mbr[446 + 4] = 0xEE;
+    mbr[446 + 5..446 + 8].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+    mbr[446 + 8..446 + 12].copy_from_slice(&1u32.to_le_bytes());
+    let mbr_sectors = (disk_sectors - 1).min(u32::MAX as u64) as u32;
+    mbr[446 + 12..446 + 16].copy_from_slice(&mbr_sectors.to_le_bytes());
+    mbr[510] = 0x55;
+    mbr[511] = 0xAA;
+
+    let entries = gpt_entries(parts, &first_lbas, seed);
+    let entries_crc = crc32(&entries);
+    let last = disk_sectors - 1;
+    let backup_entries = last - GPT_ENTRY_SECTORS;
+    disk[at(1)..at(2)].copy_from_slice(&gpt_header(1, last, 2, disk_sectors, entries_crc, seed));
+    disk[at(2)..at(2) + entries.len()].copy_from_slice(&entries);
+    disk[at(backup_entries)..at(last)].copy_from_slice(&entries);
+    disk[at(last)..].copy_from_slice(&gpt_header(
+        last,
+        1,
+        backup_entries,
+        disk_sectors,
+        entries_crc,
+        seed,
+    ));
+
+    for (part, &first_lba) in parts.iter().zip(&first_lbas) {
+        disk[at(first_lba)..at(first_lba) + part.data.len()].copy_from_slice(part.data);
+    }
+
+    Ok((disk, first_lbas))
+}
@@ -3,14 +3,37 @@
 //! Creates a bootable UEFI disk image from the compiled kernel.
 //! Uses the bootloader 0.11+ crate for image creation.
 //!
+//! With `--ab`, additionally creates `veridian-ab.img`, an A/B boot disk with
+//! two kernel slots and a boot-selection record for updates with automatic
+//! rollback (see `ab.rs` and `kernel/src/bootslot.rs`).
+//!
+//! With `--rootfs-dir`, additionally creates `veridian-disk.img`, a single
+//! GPT disk with the EFI system partition and a BlockFS root partition built
+//! from the directory (see `disk.rs`). The same BlockFS image is used as the
+//! A/B disk's root filesystem when both options are given.
+//!
+//! With `--cmdline`, the kernel command line is embedded into a copy of the
+//! kernel (see `cmdline.rs`), which all images are then built from.
+//!
+//! With `--initramfs`, a newc cpio archive (or a directory, packed by
+//! `cpio.rs`) is loaded by the bootloader as a ramdisk; the kernel unpacks
+//! it into a tmpfs root at boot (see `kernel/src/fs/initramfs.rs`).
+//!
 //! Note: BIOS mode is not supported because bootloader 0.11's BIOS stage
 //! compiles 16-bit real mode code that fails with R_386_16 relocation errors
 //! on newer LLVM toolchains. UEFI mode avoids this entirely.
 
+mod ab;
+mod cmdline;
+mod cpio;
+mod disk;
+mod gpt;
+
+use std::path::{Path, PathBuf};
+
 use anyhow::{Context, Result};
 use bootloader::UefiBoot;
 use clap::Parser;
-use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "bootimage-builder")]
@@ -23,6 +46,37 @@ struct Args {
     /// Output directory for the disk images
     #[arg(short, long, default_value = ".")]
     output: PathBuf,
+
+    /// Also create an A/B boot disk (veridian-ab.img)
+    #[arg(long)]
+    ab: bool,
+
+    /// Kernel slot size in MiB for --ab (default: twice the kernel, rounded up)
+    #[arg(long)]
+    slot_size: Option<u64>,
+
+    /// TAR root filesystem to add to the A/B disk as `veridian-rootfs`
+    #[arg(long, conflicts_with = "rootfs_dir")]
+    rootfs: Option<PathBuf>,
+
+    /// Directory to build a BlockFS root filesystem from; creates a combined
+    /// boot + root disk (veridian-disk.img)
+    #[arg(long)]
+    rootfs_dir: Option<PathBuf>,
+
+    /// Root filesystem size in MiB for --rootfs-dir (default: sized to the
+    /// directory)
+    #[arg(long, requires = "rootfs_dir")]
+    rootfs_size: Option<u64>,
+
+    /// Initramfs to load with the kernel: a newc cpio archive, or a
+    /// directory to pack into one
+    #[arg(long)]
+    initramfs: Option<PathBuf>,
+
+    /// Kernel command line to embed, e.g. "loglevel=debug root=none"
+    #[arg(long)]
+    cmdline: Option<String>,
 }
 
 fn main() -> Result<()> {
@@ -40,26 +94,141 @@ fn main() -> Result<()> {
     }
 
     // Create output directory if needed
-    std::fs::create_dir_all(&args.output)
-        .context("Failed to create output directory")?;
+    std::fs::create_dir_all(&args.output).context("Failed to create output directory")?;
+
+    let initramfs = match &args.initramfs {
+        Some(path) => Some(prepare_initramfs(path, &args.output)?),
+        None => None,
+    };
 
-    create_uefi_image(&args.kernel, &args.output)?;
+    let kernel = match &args.cmdline {
+        Some(line) => cmdline::patch_kernel(&args.kernel, line, &args.output)?,
+        None => args.kernel.clone(),
+    };
+
+    let rootfs = match (&args.rootfs, &args.rootfs_dir) {
+        (Some(path), _) => Some(std::fs::read(path).context("Failed to read rootfs")?),
+        (None, Some(dir)) => Some(disk::blockfs_image(dir, args.rootfs_size)?),
+        (None, None) => None,
+    };
+
+    let uefi_image_path = create_uefi_image(&kernel, &args.output, initramfs.as_deref())?;
+    if let (Some(blockfs), Some(_)) = (&rootfs, &args.rootfs_dir) {
+        create_combined_image(&uefi_image_path, blockfs, &args.output)?;
+    }
+    if args.ab {
+        create_ab_image(
+            &args,
+            &kernel,
+            &args.output,
+            initramfs.as_deref(),
+            rootfs.as_deref(),
+        )?;
+    }
 
     println!("\nDisk image creation complete!");
     Ok(())
 }
 
-fn create_uefi_image(kernel_path: &PathBuf, output_dir: &PathBuf) -> Result<()> {
+/// Path of the cpio archive to load as the initramfs, packing `path` into
+/// `veridian-initramfs.cpio` in the output directory if it is a directory.
+fn prepare_initramfs(path: &Path, output_dir: &Path) -> Result<PathBuf> {
+    if !path.is_dir() {
+        if !path.exists() {
+            anyhow::bail!("Initramfs not found: {}", path.display());
+        }
+        return Ok(path.to_path_buf());
+    }
+
+    println!("Packing initramfs from {}...", path.display());
+    let archive = cpio::pack_dir(path)?;
+    let archive_path = output_dir.join("veridian-initramfs.cpio");
+    std::fs::write(&archive_path, &archive).context("Failed to write initramfs")?;
+    println!(
+        "  Created: {} ({} KiB)",
+        archive_path.display(),
+        archive.len().div_ceil(1024)
+    );
+    Ok(archive_path)
+}
+
+fn create_uefi_image(
+    kernel_path: &Path,
+    output_dir: &Path,
+    initramfs: Option<&Path>,
+) -> Result<PathBuf> {
     println!("Creating UEFI disk image...");
 
     let uefi_image_path = output_dir.join("veridian-uefi.img");
 
     // Create UEFI bootable disk image
-    let uefi_boot = UefiBoot::new(kernel_path);
+    let mut uefi_boot = UefiBoot::new(kernel_path);
+    if let Some(initramfs) = initramfs {
+        uefi_boot.set_ramdisk(initramfs);
+    }
     uefi_boot
         .create_disk_image(&uefi_image_path)
         .context("Failed to create UEFI disk image")?;
 
     println!("  Created: {}", uefi_image_path.display());
+    Ok(uefi_image_path)
+}
+
+fn create_combined_image(uefi_image_path: &Path, rootfs: &[u8], output_dir: &Path) -> Result<()> {
+    println!("Creating combined boot + root disk image...");
+
+    let stock = std::fs::read(uefi_image_path).context("Failed to read UEFI disk image")?;
+    let disk = disk::build(&stock, rootfs)?;
+    let disk_image_path = output_dir.join("veridian-disk.img");
+    std::fs::write(&disk_image_path, disk).context("Failed to write combined disk image")?;
+
+    println!(
+        "  Created: {} (root filesystem of {} MiB)",
+        disk_image_path.display(),
+        rootfs.len() >> 20
+    );
+    Ok(())
+}
+
+fn create_ab_image(
+    args: &Args,
+    kernel_path: &Path,
+    output_dir: &Path,
+    initramfs: Option<&Path>,
+    rootfs: Option<&[u8]>,
+) -> Result<()> {
+    println!("Creating A/B disk image...");
+
+    let kernel = std::fs::read(kernel_path).context("Failed to read kernel")?;
+    let slot_size = match args.slot_size {
+        Some(mib) => mib << 20,
+        None => ab::default_slot_size(kernel.len()),
+    };
+
+    // The bootloader image for the padded kernel provides the ESP
+    let padded_path = output_dir.join("veridian-ab-kernel.tmp");
+    let stock_path = output_dir.join("veridian-ab-esp.tmp");
+    std::fs::write(&padded_path, ab::pad_kernel(&kernel, slot_size)?)
+        .context("Failed to write padded kernel")?;
+    let mut stock_boot = UefiBoot::new(&padded_path);
+    if let Some(initramfs) = initramfs {
+        stock_boot.set_ramdisk(initramfs);
+    }
+    let stock = stock_boot
+        .create_disk_image(&stock_path)
+        .context("Failed to create UEFI disk image")
+        .and_then(|_| std::fs::read(&stock_path).context("Failed to read UEFI disk image"));
+    let _ = std::fs::remove_file(&padded_path);
+    let _ = std::fs::remove_file(&stock_path);
+
+    let disk = ab::build(&stock?, &kernel, slot_size, rootfs)?;
+    let ab_image_path = output_dir.join("veridian-ab.img");
+    std::fs::write(&ab_image_path, disk).context("Failed to write A/B disk image")?;
+
+    println!(
+        "  Created: {} (slots of {} MiB)",
+        ab_image_path.display(),
+        slot_size >> 20
+    );
     Ok(())
 }
@@ -20,15 +20,26 @@ NC='\033[0m'
 
 echo -e "${YELLOW}VeridianOS Bootimage Builder (UEFI)${NC}"
 
-# Parse arguments
+# Parse arguments; anything after the output directory (e.g. --ab,
+# --rootfs <tar>) is passed to bootimage-builder
 KERNEL_PATH="${1:-}"
 OUTPUT_DIR="${2:-}"
+shift $(( $# < 2 ? $# : 2 ))
+EXTRA_ARGS=("$@")
 
 if [ -z "$KERNEL_PATH" ]; then
-    echo "Usage: $0 <kernel-elf-path> [output-dir]"
+    echo "Usage: $0 <kernel-elf-path> [output-dir] [builder options]"
     echo ""
     echo "Example:"
     echo "  $0 target/x86_64-veridian/debug/veridian-kernel target/x86_64-veridian/debug"
+    echo ""
+    echo "A/B boot disk with two kernel slots and a root filesystem partition:"
+    echo "  $0 target/x86_64-veridian/debug/veridian-kernel target/x86_64-veridian/debug \\"
+    echo "    --ab --rootfs target/rootfs.tar"
+    echo ""
+    echo "Single disk with the boot partition and a BlockFS root built from a directory:"
+    echo "  $0 target/x86_64-veridian/debug/veridian-kernel target/x86_64-veridian/debug \\"
+    echo "    --rootfs-dir target/rootfs-busybox"
     exit 1
 fi
 
@@ -98,6 +109,9 @@ cp -r "$BUILDER_SRC"/* "$BUILD_DIR/"
 # Remove the .cargo config that tried to override workspace settings
 rm -rf "$BUILD_DIR/.cargo"
 
+# Point the mkfs-blockfs library dependency back at the source tree
+sed -i 's|path = "../mkfs-blockfs"|path = "'"$SCRIPT_DIR"'/mkfs-blockfs"|' "$BUILD_DIR/Cargo.toml"
+
 # Build the tool in isolation (requires nightly for bootloader build.rs)
 echo "Building bootimage-builder tool..."
 cd "$BUILD_DIR"
@@ -111,6 +125,7 @@ fi
 echo "Creating UEFI disk image..."
 "$BUILD_DIR/target/release/bootimage-builder" \
     --kernel "$FULL_KERNEL_PATH" \
-    --output "$FULL_OUTPUT_DIR"
+    --output "$FULL_OUTPUT_DIR" \
+    "${EXTRA_ARGS[@]}"
 
 echo -e "${GREEN}Done!${NC}"
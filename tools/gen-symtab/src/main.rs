@@ -0,0 +1,266 @@
+//! gen-symtab -- Embed a compressed symbol table into a linked kernel
+//!
+//! The kernel reserves a fixed-size `.ksymtab` section holding an empty
+//! table (`kernel/src/crash/symbols.rs` defines the format). After linking,
+//! this tool lists the kernel's function symbols with `nm`, encodes them and
+//! writes the table into that section in place, so panics and faults can
+//! print `function+offset` without help from the host. The section's size
+//! and the image layout do not change.
+//!
+//! Names are demangled and stripped of their hash, then front-coded: each
+//! name stores only the bytes that differ from the previous one, restarting
+//! every 16 names so a lookup decodes at most one block. Functions of one
+//! module sit next to each other, so this roughly halves the string area.
+//!
+//! If the table still does not fit, the smallest functions are left out; a
+//! frame in one of them is then attributed to the preceding symbol.
+//!
+//! Usage:
+//!   gen-symtab <kernel-elf>
+//!
+//! `nm` must understand the kernel's architecture (GNU binutils, llvm-nm or
+//! rust-nm); set NM to choose one.
+
+use std::{env, fs, process::Command};
+
+const KSYMTAB_SECTION: &str = ".ksymtab";
+const KSYMTAB_MAGIC: &[u8; 4] = b"KSYM";
+const KSYMTAB_VERSION: u32 = 2;
+const HEADER_LEN: usize = 40;
+const ENTRY_LEN: usize = 8;
+/// Names per front-coded block.
+const BLOCK: usize = 16;
+const MAX_NAME: usize = 255;
+
+#[derive(Clone)]
+struct Symbol {
+    addr: u64,
+    size: u64,
+    name: Vec<u8>,
+}
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes(buf[off..off + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(buf[off..off + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(buf[off..off + 8].try_into().unwrap())
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("gen-symtab: {}", msg);
+    std::process::exit(1);
+}
+
+/// File offset, size and address of section `name` of an ELF64 image.
+fn find_section(image: &[u8], name: &str) -> Option<(usize, usize, u64)> {
+    if image.len() < 64 || &image[..4] != b"\x7fELF" || image[4] != 2 || image[5] != 1 {
+        fail("not a little-endian ELF64 file");
+    }
+    let shoff = read_u64(image, 0x28) as usize;
+    let shentsize = read_u16(image, 0x3A) as usize;
+    let shnum = read_u16(image, 0x3C) as usize;
+    let shstrndx = read_u16(image, 0x3E) as usize;
+    let header = |index: usize| shoff + index * shentsize;
+
+    let strtab = read_u64(image, header(shstrndx) + 0x18) as usize;
+    (0..shnum).find_map(|index| {
+        let h = header(index);
+        let start = strtab + read_u32(image, h) as usize;
+        let end = start + image[start..].iter().position(|&b| b == 0)?;
+        (&image[start..end] == name.as_bytes()).then(|| {
+            (
+                read_u64(image, h + 0x18) as usize,
+                read_u64(image, h + 0x20) as usize,
+                read_u64(image, h + 0x10),
+            )
+        })
+    })
+}
+
+/// Drop a Rust legacy-mangling hash (`::h0123456789abcdef`).
+fn strip_hash(name: &str) -> &str {
+    match name.rfind("::h") {
+        Some(at)
+            if name.len() - at == 19 && name[at + 3..].bytes().all(|b| b.is_ascii_hexdigit()) =>
+        {
+            &name[..at]
+        }
+        _ => name,
+    }
+}
+
+/// Every function symbol of `kernel`, sorted by address, one per address.
+fn function_symbols(kernel: &str) -> Vec<Symbol> {
+    let nm = env::var("NM").unwrap_or_else(|_| String::from("nm"));
+    let output = Command::new(&nm)
+        .args([
+            "--defined-only",
+            "--numeric-sort",
+            "--print-size",
+            "--demangle",
+            kernel,
+        ])
+        .output()
+        .unwrap_or_else(|e| fail(&format!("cannot run {}: {}", nm, e)));
+    if !output.status.success() {
+        fail(&format!(
+            "{} failed: {}",
+            nm,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let mut symbols: Vec<Symbol> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        // `addr [size] kind name`; demangled names may contain spaces.
+        let fields: Vec<&str> = line.splitn(4, ' ').collect();
+        let (addr, size, kind, name) = match fields[..] {
+            [addr, kind, ..] if kind.len() == 1 => (addr, "0", kind, &line[addr.len() + 3..]),
+            [addr, size, kind, name] => (addr, size, kind, name),
+            _ => continue,
+        };
+        if kind != "t" && kind != "T" {
+            continue;
+        }
+        let (Ok(addr), Ok(size)) = (u64::from_str_radix(addr, 16), u64::from_str_radix(size, 16))
+        else {
+            continue;
+        };
+        let mut name = strip_hash(name).as_bytes().to_vec();
+        name.truncate(MAX_NAME);
+        // Aliases share an address: keep the first name, largest size.
+        match symbols.last_mut() {
+            Some(last) if last.addr == addr => last.size = last.size.max(size),
+            _ => symbols.push(Symbol { addr, size, name }),
+        }
+    }
+    symbols
+}
+
+/// Front-code the names of `symbols`: the block index and the name bytes.
+fn encode_names(symbols: &[Symbol]) -> (Vec<u32>, Vec<u8>) {
+    let mut index = Vec::new();
+    let mut names = Vec::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        let prefix = if i % BLOCK == 0 {
+            index.push(names.len() as u32);
+            0
+        } else {
+            let prev = &symbols[i - 1].name;
+            prev.iter()
+                .zip(&symbol.name)
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+        let suffix = &symbol.name[prefix..];
+        names.push(prefix as u8);
+        names.push(suffix.len() as u8);
+        names.extend_from_slice(suffix);
+    }
+    (index, names)
+}
+
+fn table_len(symbols: &[Symbol]) -> usize {
+    let (index, names) = encode_names(symbols);
+    HEADER_LEN + symbols.len() * ENTRY_LEN + index.len() * 4 + names.len()
+}
+
+/// Encode the table for a section linked at `anchor` and `capacity` bytes
+/// long, leaving out the smallest functions if needed.
+fn build_table(mut symbols: Vec<Symbol>, anchor: u64, capacity: usize) -> (Vec<u8>, usize) {
+    if table_len(&symbols) > capacity {
+        // Keep the largest functions that fit, back in address order.
+        let mut by_size: Vec<usize> = (0..symbols.len()).collect();
+        by_size.sort_by_key(|&i| std::cmp::Reverse(symbols[i].size));
+        let subset = |n: usize| -> Vec<Symbol> {
+            let mut keep = by_size[..n].to_vec();
+            keep.sort_unstable();
+            keep.iter().map(|&i| symbols[i].clone()).collect()
+        };
+        let (mut lo, mut hi) = (0, symbols.len());
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            if table_len(&subset(mid)) <= capacity {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        symbols = subset(lo);
+    }
+
+    let base = symbols.first().map_or(0, |s| s.addr);
+    if symbols
+        .last()
+        .is_some_and(|s| s.addr - base > u32::MAX as u64)
+    {
+        fail("kernel text spans more than 4 GiB");
+    }
+    let (index, names) = encode_names(&symbols);
+    let index_off = HEADER_LEN + symbols.len() * ENTRY_LEN;
+    let names_off = index_off + index.len() * 4;
+
+    let mut table = Vec::with_capacity(capacity);
+    table.extend_from_slice(KSYMTAB_MAGIC);
+    table.extend_from_slice(&KSYMTAB_VERSION.to_le_bytes());
+    table.extend_from_slice(&(symbols.len() as u32).to_le_bytes());
+    table.extend_from_slice(&(index_off as u32).to_le_bytes());
+    table.extend_from_slice(&(names_off as u32).to_le_bytes());
+    table.extend_from_slice(&0u32.to_le_bytes());
+    table.extend_from_slice(&anchor.to_le_bytes());
+    table.extend_from_slice(&base.to_le_bytes());
+    for symbol in &symbols {
+        table.extend_from_slice(&((symbol.addr - base) as u32).to_le_bytes());
+        table.extend_from_slice(&(symbol.size.min(u32::MAX as u64) as u32).to_le_bytes());
+    }
+    for offset in &index {
+        table.extend_from_slice(&offset.to_le_bytes());
+    }
+    table.extend_from_slice(&names);
+    table.resize(capacity, 0);
+    (table, symbols.len())
+}
+
+fn print_usage() {
+    eprintln!("Usage: gen-symtab <kernel-elf>");
+    eprintln!();
+    eprintln!("Fills the kernel's .ksymtab section with its function symbols, in place.");
+    eprintln!("Set NM to the nm to use (default: nm).");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 || args[1] == "--help" || args[1] == "-h" {
+        print_usage();
+        std::process::exit(if args.len() == 2 { 0 } else { 1 });
+    }
+    let kernel = &args[1];
+
+    let mut image = fs::read(kernel).unwrap_or_else(|e| fail(&format!("{}: {}", kernel, e)));
+    let (offset, size, anchor) = find_section(&image, KSYMTAB_SECTION)
+        .unwrap_or_else(|| fail("no .ksymtab section (kernel built without crate::crash?)"));
+    if &image[offset..offset + 4] != KSYMTAB_MAGIC {
+        fail(".ksymtab does not hold a symbol table");
+    }
+
+    let symbols = function_symbols(kernel);
+    let total = symbols.len();
+    let (table, count) = build_table(symbols, anchor, size);
+    image[offset..offset + size].copy_from_slice(&table);
+    fs::write(kernel, &image).unwrap_or_else(|e| fail(&format!("{}: {}", kernel, e)));
+
+    if count < total {
+        println!(
+            "gen-symtab: {} symbols ({} small ones left out)",
+            count,
+            total - count
+        );
+    } else {
+        println!("gen-symtab: {} symbols", count);
+    }
+}
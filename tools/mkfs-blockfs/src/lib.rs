@@ -0,0 +1,714 @@
+//! mkfs-blockfs -- Create and populate VeridianOS BlockFS disk images
+//!
+//! Host-side library (runs on Linux) that builds a raw image containing a
+//! pre-formatted BlockFS filesystem, optionally populated with files from a
+//! host directory. Used by the `mkfs-blockfs` binary and by
+//! `bootimage-builder --rootfs-dir`.
+//!
+//! Layouts, constants and serialization come from the `blockfs-format` crate,
+//! which the kernel's BlockFS driver uses as well.
+//!
+//! All-zero blocks of host files (including holes in sparse files) are left
+//! unallocated; BlockFS reads them back as zeros.
+//!
+//! Extended attributes of host files (user., security. and trusted.
+//! namespaces, plus POSIX ACLs) are copied into a per-inode xattr block.
+//!
+//! Images are written in the current (v2) format unless
+//! [`BlockFsBuilder::with_format`] asks for v1. Populated inodes carry the
+//! host's access, change, modification and creation times.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::{self, File},
+    io::{self, Cursor, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub use blockfs_format::{
+    bitmap_blocks, computed_first_data_block, inode_table_blocks, InodeFormat, BLOCK_SIZE,
+    ROOT_INODE,
+};
+use blockfs_format::{
+    dir_entry_len, encode_dir_entry, encode_xattr_block, DiskInode, Superblock, DIRECT_BLOCKS,
+    FT_DIR, FT_REG_FILE, NANOS_PER_SEC, PTRS_PER_BLOCK,
+};
+
+/// Default inode count for an image of `block_count` blocks: one inode per
+/// 16 KiB, which is generous for small files.
+pub fn default_inode_count(block_count: u32) -> u32 {
+    (block_count / 4).clamp(672, 65536)
+}
+
+/// BlockFS image builder
+pub struct BlockFsBuilder {
+    block_count: u32,
+    inode_count: u32,
+    format: InodeFormat,
+    first_data_block: u32,
+    bitmap: Vec<u8>,
+    inodes: Vec<DiskInode>,
+    /// Data blocks (only used ones are stored; written by block index)
+    blocks: Vec<Vec<u8>>,
+    next_free_inode: u32,
+    /// File blocks left as holes because they were all zeros
+    hole_blocks: u64,
+}
+
+impl BlockFsBuilder {
+    /// An empty filesystem of `block_count` 4 KiB blocks with room for
+    /// `inode_count` inodes.
+    pub fn new(block_count: u32, inode_count: u32) -> Self {
+        Self::with_format(block_count, inode_count, InodeFormat::V2)
+    }
+
+    /// Like [`BlockFsBuilder::new`], but writing inodes in `format`. `V1`
+    /// images mount on kernels that predate the v2 format.
+    pub fn with_format(block_count: u32, inode_count: u32, format: InodeFormat) -> Self {
+        let first_data = computed_first_data_block(block_count, inode_count, format);
+        let bitmap_size = (block_count as usize).div_ceil(8);
+        let mut bitmap = vec![0u8; bitmap_size];
+
+        // Mark metadata blocks as allocated
+        for b in 0..first_data {
+            let byte_idx = (b / 8) as usize;
+            let bit = (b % 8) as usize;
+            if byte_idx < bitmap.len() {
+                bitmap[byte_idx] |= 1 << bit;
+            }
+        }
+
+        let mut inodes = vec![DiskInode::default(); inode_count as usize];
+
+        // Root inode (inode 0): directory, rwxr-xr-x
+        inodes[0].mode = 0x41ED;
+        inodes[0].links_count = 2;
+
+        let mut blocks = Vec::with_capacity(block_count as usize);
+        for _ in 0..block_count {
+            blocks.push(vec![0u8; BLOCK_SIZE]);
+        }
+
+        let mut builder = Self {
+            block_count,
+            inode_count,
+            format,
+            first_data_block: first_data,
+            bitmap,
+            inodes,
+            blocks,
+            next_free_inode: 1,
+            hole_blocks: 0,
+        };
+
+        // Create "." and ".." entries in root
+        builder.write_dir_entry(0, 0, ".", FT_DIR);
+        builder.write_dir_entry(0, 0, "..", FT_DIR);
+
+        builder
+    }
+
+    fn allocate_block(&mut self) -> Option<u32> {
+        for byte_idx in 0..self.bitmap.len() {
+            if self.bitmap[byte_idx] != 0xFF {
+                for bit in 0..8 {
+                    if (self.bitmap[byte_idx] & (1 << bit)) == 0 {
+                        self.bitmap[byte_idx] |= 1 << bit;
+                        let block_num = (byte_idx * 8 + bit) as u32;
+                        if block_num < self.block_count {
+                            return Some(block_num);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn allocate_inode(&mut self) -> Option<u32> {
+        if self.next_free_inode >= self.inode_count {
+            return None;
+        }
+        let idx = self.next_free_inode;
+        self.next_free_inode += 1;
+        Some(idx)
+    }
+
+    pub fn free_blocks_count(&self) -> u32 {
+        let mut count = 0u32;
+        for byte_idx in 0..self.bitmap.len() {
+            for bit in 0..8 {
+                let block_num = byte_idx * 8 + bit;
+                if block_num < self.block_count as usize
+                    && (self.bitmap[byte_idx] & (1 << bit)) == 0
+                {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Ensure a logical block for an inode is allocated, creating indirect
+    /// blocks as needed.
+    fn ensure_block(&mut self, inode_idx: u32, logical_block: usize) -> u32 {
+        if logical_block < DIRECT_BLOCKS {
+            let existing = self.inodes[inode_idx as usize].direct_blocks[logical_block];
+            if existing != 0 {
+                return existing;
+            }
+            let blk = self.allocate_block().expect("out of blocks");
+            self.inodes[inode_idx as usize].direct_blocks[logical_block] = blk;
+            self.inodes[inode_idx as usize].blocks += 1;
+            blk
+        } else if logical_block < DIRECT_BLOCKS + PTRS_PER_BLOCK {
+            // Single indirect
+            let mut indirect = self.inodes[inode_idx as usize].indirect_block;
+            if indirect == 0 {
+                indirect = self.allocate_block().expect("out of blocks");
+                self.blocks[indirect as usize] = vec![0u8; BLOCK_SIZE];
+                self.inodes[inode_idx as usize].indirect_block = indirect;
+                self.inodes[inode_idx as usize].blocks += 1;
+            }
+            let idx = logical_block - DIRECT_BLOCKS;
+            let off = idx * 4;
+            let existing = u32::from_le_bytes([
+                self.blocks[indirect as usize][off],
+                self.blocks[indirect as usize][off + 1],
+                self.blocks[indirect as usize][off + 2],
+                self.blocks[indirect as usize][off + 3],
+            ]);
+            if existing != 0 {
+                return existing;
+            }
+            let blk = self.allocate_block().expect("out of blocks");
+            self.blocks[indirect as usize][off..off + 4].copy_from_slice(&blk.to_le_bytes());
+            self.inodes[inode_idx as usize].blocks += 1;
+            blk
+        } else if logical_block < DIRECT_BLOCKS + PTRS_PER_BLOCK + PTRS_PER_BLOCK * PTRS_PER_BLOCK {
+            // Double indirect
+            let mut dbl_indirect = self.inodes[inode_idx as usize].double_indirect_block;
+            if dbl_indirect == 0 {
+                dbl_indirect = self.allocate_block().expect("out of blocks");
+                self.blocks[dbl_indirect as usize] = vec![0u8; BLOCK_SIZE];
+                self.inodes[inode_idx as usize].double_indirect_block = dbl_indirect;
+                self.inodes[inode_idx as usize].blocks += 1;
+            }
+
+            let rel = logical_block - DIRECT_BLOCKS - PTRS_PER_BLOCK;
+            let l1_idx = rel / PTRS_PER_BLOCK;
+            let l2_idx = rel % PTRS_PER_BLOCK;
+
+            // Read or allocate L1 indirect block
+            let l1_off = l1_idx * 4;
+            let mut l1_block = u32::from_le_bytes([
+                self.blocks[dbl_indirect as usize][l1_off],
+                self.blocks[dbl_indirect as usize][l1_off + 1],
+                self.blocks[dbl_indirect as usize][l1_off + 2],
+                self.blocks[dbl_indirect as usize][l1_off + 3],
+            ]);
+            if l1_block == 0 {
+                l1_block = self.allocate_block().expect("out of blocks");
+                self.blocks[l1_block as usize] = vec![0u8; BLOCK_SIZE];
+                self.blocks[dbl_indirect as usize][l1_off..l1_off + 4]
+                    .copy_from_slice(&l1_block.to_le_bytes());
+                self.inodes[inode_idx as usize].blocks += 1;
+            }
+
+            // Read or allocate data block
+            let l2_off = l2_idx * 4;
+            let existing = u32::from_le_bytes([
+                self.blocks[l1_block as usize][l2_off],
+                self.blocks[l1_block as usize][l2_off + 1],
+                self.blocks[l1_block as usize][l2_off + 2],
+                self.blocks[l1_block as usize][l2_off + 3],
+            ]);
+            if existing != 0 {
+                return existing;
+            }
+            let blk = self.allocate_block().expect("out of blocks");
+            self.blocks[l1_block as usize][l2_off..l2_off + 4].copy_from_slice(&blk.to_le_bytes());
+            self.inodes[inode_idx as usize].blocks += 1;
+            blk
+        } else {
+            panic!(
+                "file too large: logical block {} exceeds double indirect range",
+                logical_block
+            );
+        }
+    }
+
+    /// Write data to an inode's data blocks
+    fn write_inode_data(&mut self, inode_idx: u32, offset: usize, data: &[u8]) {
+        let mut current_offset = offset;
+        let mut bytes_written = 0;
+
+        while bytes_written < data.len() {
+            let logical_block = current_offset / BLOCK_SIZE;
+            let block_offset = current_offset % BLOCK_SIZE;
+            let copy_len = (BLOCK_SIZE - block_offset).min(data.len() - bytes_written);
+
+            let phys_block = self.ensure_block(inode_idx, logical_block);
+            self.blocks[phys_block as usize][block_offset..block_offset + copy_len]
+                .copy_from_slice(&data[bytes_written..bytes_written + copy_len]);
+
+            bytes_written += copy_len;
+            current_offset += copy_len;
+        }
+
+        // Update inode size
+        let new_end = offset + data.len();
+        if new_end as u64 > self.inodes[inode_idx as usize].size {
+            self.inodes[inode_idx as usize].size = new_end as u64;
+        }
+    }
+
+    /// Add a directory entry to a directory inode
+    ///
+    /// Like the kernel, entries never straddle a block boundary: one that
+    /// does not fit in the rest of the current block starts the next one.
+    fn write_dir_entry(&mut self, dir_inode: u32, child_inode: u32, name: &str, file_type: u8) {
+        let mut entry = vec![0u8; dir_entry_len(name.len())];
+        let rec_len = encode_dir_entry(&mut entry, 0, child_inode, name.as_bytes(), file_type);
+
+        let mut offset = self.inodes[dir_inode as usize].size as usize;
+        if offset % BLOCK_SIZE + rec_len > BLOCK_SIZE {
+            offset = offset.next_multiple_of(BLOCK_SIZE);
+        }
+        self.write_inode_data(dir_inode, offset, &entry);
+    }
+
+    /// Create a file inode with contents `data` and add it to a parent
+    /// directory. Returns the new inode number.
+    pub fn create_file(&mut self, parent_inode: u32, name: &str, data: &[u8], mode: u16) -> u32 {
+        assert!(
+            data.len() as u64 <= self.format.max_file_size(),
+            "file too large: {} bytes exceeds the {:?} inode size limit",
+            data.len(),
+            self.format
+        );
+        let inode_idx = self.allocate_inode().expect("out of inodes");
+        self.inodes[inode_idx as usize].mode = mode;
+        self.inodes[inode_idx as usize].links_count = 1;
+
+        // Write file data, leaving all-zero blocks as holes
+        for (i, chunk) in data.chunks(BLOCK_SIZE).enumerate() {
+            if chunk.iter().any(|&b| b != 0) {
+                self.write_inode_data(inode_idx, i * BLOCK_SIZE, chunk);
+            } else {
+                self.hole_blocks += 1;
+            }
+        }
+        self.inodes[inode_idx as usize].size = data.len() as u64;
+
+        // Add to parent directory
+        self.write_dir_entry(parent_inode, inode_idx, name, FT_REG_FILE);
+
+        inode_idx
+    }
+
+    /// Create a directory inode and add it to a parent directory. Returns
+    /// the new inode number.
+    pub fn create_directory(&mut self, parent_inode: u32, name: &str) -> u32 {
+        let inode_idx = self.allocate_inode().expect("out of inodes");
+        self.inodes[inode_idx as usize].mode = 0x41ED; // directory, rwxr-xr-x
+        self.inodes[inode_idx as usize].links_count = 2;
+
+        // Create "." and ".." entries
+        self.write_dir_entry(inode_idx, inode_idx, ".", FT_DIR);
+        self.write_dir_entry(inode_idx, parent_inode, "..", FT_DIR);
+
+        // Add to parent directory
+        self.write_dir_entry(parent_inode, inode_idx, name, FT_DIR);
+
+        // Increment parent's link count (subdirectory ".." points to parent)
+        self.inodes[parent_inode as usize].links_count += 1;
+
+        inode_idx
+    }
+
+    /// Store `attrs` in a new xattr block for an inode, using the kernel's
+    /// xattr block layout. Attribute sets that do not fit in one block are
+    /// skipped with a warning.
+    fn write_xattrs(&mut self, inode_idx: u32, attrs: &BTreeMap<String, Vec<u8>>, path: &Path) {
+        if attrs.is_empty() {
+            return;
+        }
+
+        let mut block = vec![0u8; BLOCK_SIZE];
+        let entries = attrs.iter().map(|(n, v)| (n.as_bytes(), v.as_slice()));
+        if encode_xattr_block(entries, &mut block).is_err() {
+            eprintln!(
+                "Warning: extended attributes of {} exceed one block, skipped",
+                path.display()
+            );
+            return;
+        }
+
+        let block_num = self.allocate_block().expect("out of blocks");
+        self.blocks[block_num as usize] = block;
+        self.inodes[inode_idx as usize].xattr_block = block_num;
+    }
+
+    /// Copy the timestamps of a host file into an inode.
+    fn set_times(&mut self, inode_idx: u32, metadata: &fs::Metadata) {
+        let inode = &mut self.inodes[inode_idx as usize];
+        inode.atime = epoch_ns(metadata.accessed());
+        inode.mtime = epoch_ns(metadata.modified());
+        inode.crtime = epoch_ns(metadata.created());
+        inode.ctime = host_ctime_ns(metadata).unwrap_or(inode.mtime);
+    }
+
+    /// Populate from a host directory tree
+    pub fn populate_from_dir(&mut self, host_dir: &Path, fs_inode: u32) {
+        if let Ok(metadata) = fs::metadata(host_dir) {
+            self.set_times(fs_inode, &metadata);
+        }
+
+        let mut queue: VecDeque<(PathBuf, u32)> = VecDeque::new();
+        queue.push_back((host_dir.to_path_buf(), fs_inode));
+
+        while let Some((dir_path, parent_inode)) = queue.pop_front() {
+            let entries = match fs::read_dir(&dir_path) {
+                Ok(e) => e,
+                Err(e) => {
+                    eprintln!("Warning: cannot read {}: {}", dir_path.display(), e);
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let entry = match entry {
+                    Ok(e) => e,
+                    Err(_) => continue,
+                };
+
+                let name = entry.file_name();
+                let name_str = name.to_string_lossy();
+                let path = entry.path();
+                let metadata = match entry.metadata() {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+
+                let file_type = metadata.file_type();
+
+                if file_type.is_dir() {
+                    let child_inode = self.create_directory(parent_inode, &name_str);
+                    self.set_times(child_inode, &metadata);
+                    self.write_xattrs(child_inode, &host_xattrs(&path), &path);
+                    queue.push_back((path, child_inode));
+                } else if file_type.is_symlink() {
+                    // Expand symlinks as copies of their target (matches TAR
+                    // loader behavior). Read the symlink target, resolve it
+                    // relative to the host directory, and copy the target file.
+                    let target = match fs::read_link(&path) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            eprintln!("Warning: cannot read symlink {}: {}", path.display(), e);
+                            continue;
+                        }
+                    };
+
+                    // Resolve relative symlink targets against the containing directory
+                    let resolved = if target.is_relative() {
+                        path.parent().unwrap_or(Path::new(".")).join(&target)
+                    } else {
+                        // Absolute symlinks: resolve relative to the populate root
+                        host_dir.join(target.strip_prefix("/").unwrap_or(&target))
+                    };
+
+                    if resolved.is_file() {
+                        let data = match fs::read(&resolved) {
+                            Ok(d) => d,
+                            Err(e) => {
+                                eprintln!(
+                                    "Warning: cannot read symlink target {}: {}",
+                                    resolved.display(),
+                                    e
+                                );
+                                continue;
+                            }
+                        };
+
+                        let mode = if is_executable(&resolved) {
+                            0x81ED // file, rwxr-xr-x
+                        } else {
+                            0x81A4 // file, rw-r--r--
+                        };
+
+                        let child_inode = self.create_file(parent_inode, &name_str, &data, mode);
+                        if let Ok(target_metadata) = fs::metadata(&resolved) {
+                            self.set_times(child_inode, &target_metadata);
+                        }
+                        self.write_xattrs(child_inode, &host_xattrs(&resolved), &resolved);
+                    } else if resolved.is_dir() {
+                        let child_inode = self.create_directory(parent_inode, &name_str);
+                        if let Ok(target_metadata) = fs::metadata(&resolved) {
+                            self.set_times(child_inode, &target_metadata);
+                        }
+                        self.write_xattrs(child_inode, &host_xattrs(&resolved), &resolved);
+                        queue.push_back((resolved, child_inode));
+                    } else {
+                        eprintln!(
+                            "Warning: symlink target not found: {} -> {}",
+                            path.display(),
+                            resolved.display()
+                        );
+                    }
+                } else if file_type.is_file() {
+                    let data = match fs::read(&path) {
+                        Ok(d) => d,
+                        Err(e) => {
+                            eprintln!("Warning: cannot read {}: {}", path.display(), e);
+                            continue;
+                        }
+                    };
+
+                    // Determine mode from host permissions
+                    let mode = if is_executable(&path) {
+                        0x81ED // file, rwxr-xr-x
+                    } else {
+                        0x81A4 // file, rw-r--r--
+                    };
+
+                    let child_inode = self.create_file(parent_inode, &name_str, &data, mode);
+                    self.set_times(child_inode, &metadata);
+                    self.write_xattrs(child_inode, &host_xattrs(&path), &path);
+                }
+                // Skip special files (block/char devices, sockets, etc.)
+            }
+        }
+    }
+
+    /// Size of the image in bytes.
+    pub fn image_size(&self) -> u64 {
+        self.block_count as u64 * BLOCK_SIZE as u64
+    }
+
+    /// Inode format of the image.
+    pub fn format(&self) -> InodeFormat {
+        self.format
+    }
+
+    /// Inodes allocated so far, including the root directory.
+    pub fn inodes_used(&self) -> u32 {
+        self.next_free_inode
+    }
+
+    /// File blocks left as holes because they were all zeros.
+    pub fn hole_blocks(&self) -> u64 {
+        self.hole_blocks
+    }
+
+    /// Write the complete image to a file
+    pub fn write_image(&self, output: &Path) -> std::io::Result<()> {
+        let mut file = File::create(output)?;
+
+        // Pre-allocate the file
+        file.set_len(self.image_size())?;
+        self.write_to(&mut file)?;
+        file.sync_all()
+    }
+
+    /// Build the complete image in memory.
+    pub fn to_image(&self) -> Vec<u8> {
+        let mut image = Cursor::new(vec![0u8; self.image_size() as usize]);
+        self.write_to(&mut image)
+            .expect("writing to memory cannot fail");
+        image.into_inner()
+    }
+
+    /// Write the image to `file`, which must already be `image_size()`
+    /// bytes of zeros: all-zero blocks are skipped.
+    fn write_to<W: Write + Seek>(&self, file: &mut W) -> std::io::Result<()> {
+        // Write superblock (block 0)
+        let mut sb = Superblock::with_format(self.block_count, self.inode_count, self.format);
+        sb.free_blocks = self.free_blocks_count();
+        sb.free_inodes = self.inode_count - self.next_free_inode;
+        let mut buf = [0u8; BLOCK_SIZE];
+        sb.encode(&mut buf);
+
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&buf)?;
+
+        // Write bitmap (blocks 1..1+B)
+        let bm_blocks = bitmap_blocks(self.block_count);
+        let bitmap_start = 1u32;
+        for i in 0..bm_blocks {
+            let mut buf = [0u8; BLOCK_SIZE];
+            let byte_offset = i as usize * BLOCK_SIZE;
+            let bytes_remaining = self.bitmap.len().saturating_sub(byte_offset);
+            let copy_len = bytes_remaining.min(BLOCK_SIZE);
+            if copy_len > 0 {
+                buf[..copy_len].copy_from_slice(&self.bitmap[byte_offset..byte_offset + copy_len]);
+            }
+            file.seek(SeekFrom::Start(
+                (bitmap_start + i) as u64 * BLOCK_SIZE as u64,
+            ))?;
+            file.write_all(&buf)?;
+        }
+
+        // Write inode table
+        let inode_start = 1 + bm_blocks;
+        let it_blocks = inode_table_blocks(self.inode_count, self.format);
+        let inode_size = self.format.inode_size();
+        for (blk_idx, chunk) in self
+            .inodes
+            .chunks(self.format.inodes_per_block())
+            .take(it_blocks as usize)
+            .enumerate()
+        {
+            let mut buf = [0u8; BLOCK_SIZE];
+            for (slot, inode) in chunk.iter().enumerate() {
+                let off = slot * inode_size;
+                inode.encode(&mut buf[off..off + inode_size], self.format);
+            }
+
+            file.seek(SeekFrom::Start(
+                (inode_start + blk_idx as u32) as u64 * BLOCK_SIZE as u64,
+            ))?;
+            file.write_all(&buf)?;
+        }
+
+        // Write data blocks
+        for (block_idx, block_data) in self.blocks.iter().enumerate() {
+            let idx = block_idx as u32;
+            if idx < self.first_data_block {
+                continue; // Skip metadata blocks
+            }
+            // Only write non-zero blocks
+            if block_data.iter().any(|&b| b != 0) {
+                file.seek(SeekFrom::Start(block_idx as u64 * BLOCK_SIZE as u64))?;
+                file.write_all(block_data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Nanoseconds since the Unix epoch, or 0 if the host does not record the
+/// time (or it predates the epoch).
+fn epoch_ns(time: io::Result<SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos().min(u64::MAX as u128) as u64)
+}
+
+/// Inode change time of a host file, which `std` only exposes on Unix.
+fn host_ctime_ns(metadata: &fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let secs = u64::try_from(metadata.ctime()).ok()?;
+        Some(secs.saturating_mul(NANOS_PER_SEC) + metadata.ctime_nsec() as u64)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(path) {
+            return metadata.permissions().mode() & 0o111 != 0;
+        }
+    }
+    false
+}
+
+/// Whether a host attribute should be carried into the image.
+///
+/// Host SELinux labels are dropped: they describe the build host's policy,
+/// not VeridianOS's.
+fn keep_xattr(name: &str) -> bool {
+    let kept_namespace = ["user.", "security.", "trusted."]
+        .iter()
+        .any(|ns| name.starts_with(ns));
+    (kept_namespace && name != "security.selinux")
+        || name == "system.posix_acl_access"
+        || name == "system.posix_acl_default"
+}
+
+/// Read the extended attributes of a host file (without following a final
+/// symlink).
+#[cfg(target_os = "linux")]
+fn host_xattrs(path: &Path) -> BTreeMap<String, Vec<u8>> {
+    use std::{
+        ffi::{c_char, c_void, CString},
+        os::unix::ffi::OsStrExt,
+    };
+
+    extern "C" {
+        fn llistxattr(path: *const c_char, list: *mut c_char, size: usize) -> isize;
+        fn lgetxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *mut c_void,
+            size: usize,
+        ) -> isize;
+    }
+
+    let mut attrs = BTreeMap::new();
+    let Ok(cpath) = CString::new(path.as_os_str().as_bytes()) else {
+        return attrs;
+    };
+
+    // SAFETY: cpath is NUL-terminated; a zero size only queries the length.
+    let len = unsafe { llistxattr(cpath.as_ptr(), std::ptr::null_mut(), 0) };
+    if len <= 0 {
+        return attrs;
+    }
+    let mut list = vec![0u8; len as usize];
+    // SAFETY: list has room for `len` bytes.
+    let len = unsafe { llistxattr(cpath.as_ptr(), list.as_mut_ptr().cast(), list.len()) };
+    if len <= 0 {
+        return attrs;
+    }
+    list.truncate(len as usize);
+
+    for name in list.split(|&b| b == 0).filter(|n| !n.is_empty()) {
+        let Ok(name_str) = std::str::from_utf8(name) else {
+            continue;
+        };
+        if !keep_xattr(name_str) {
+            continue;
+        }
+        let Ok(cname) = CString::new(name) else {
+            continue;
+        };
+        // SAFETY: both strings are NUL-terminated; a zero size queries the
+        // length.
+        let vlen = unsafe { lgetxattr(cpath.as_ptr(), cname.as_ptr(), std::ptr::null_mut(), 0) };
+        if vlen < 0 {
+            continue;
+        }
+        let mut value = vec![0u8; vlen as usize];
+        // SAFETY: value has room for `vlen` bytes.
+        let vlen = unsafe {
+            lgetxattr(
+                cpath.as_ptr(),
+                cname.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            )
+        };
+        if vlen < 0 {
+            continue;
+        }
+        value.truncate(vlen as usize);
+        attrs.insert(name_str.to_string(), value);
+    }
+    attrs
+}
+
+#[cfg(not(target_os = "linux"))]
+fn host_xattrs(_path: &Path) -> BTreeMap<String, Vec<u8>> {
+    BTreeMap::new()
+}
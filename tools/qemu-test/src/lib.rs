@@ -0,0 +1,331 @@
+//! qemu-test -- Boot-to-shell integration tests for VeridianOS
+//!
+//! Host-side library behind the `qemu-test` binary. Each scenario (see
+//! [`scenario`]) is a script of serial console steps: wait for boot
+//! messages and the shell prompt, type commands, and check what comes back.
+//! The [`runner`] plays a scenario against any [`runner::Console`]; for real
+//! runs that console is QEMU's serial port on stdio, set up per architecture
+//! by [`Arch::qemu_args`].
+//!
+//! The unit tests drive the runner with a scripted console, so they need
+//! neither QEMU nor a kernel build.
+
+pub mod runner;
+pub mod scenario;
+
+use std::{
+    fmt,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use runner::{Console, Recv};
+
+/// OVMF firmware locations tried for x86_64, as in scripts/run-veridian.sh
+const OVMF_CANDIDATES: &[&str] = &[
+    "/usr/share/edk2/x64/OVMF.4m.fd",
+    "/usr/share/OVMF/OVMF_CODE.fd",
+    "/usr/share/edk2/ovmf/OVMF_CODE.fd",
+];
+
+/// Target architecture of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X86_64,
+    Aarch64,
+    Riscv64,
+}
+
+impl Arch {
+    pub const ALL: [Arch; 3] = [Arch::X86_64, Arch::Aarch64, Arch::Riscv64];
+
+    /// Parse an architecture name as used by build-kernel.sh.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "x86_64" => Some(Self::X86_64),
+            "aarch64" => Some(Self::Aarch64),
+            "riscv64" | "riscv64gc" => Some(Self::Riscv64),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::X86_64 => "x86_64",
+            Self::Aarch64 => "aarch64",
+            Self::Riscv64 => "riscv64",
+        }
+    }
+
+    pub fn qemu_binary(self) -> &'static str {
+        match self {
+            Self::X86_64 => "qemu-system-x86_64",
+            Self::Aarch64 => "qemu-system-aarch64",
+            Self::Riscv64 => "qemu-system-riscv64",
+        }
+    }
+
+    /// Boot image produced by `./build-kernel.sh <arch> dev|release`: the
+    /// UEFI disk image on x86_64, the kernel ELF elsewhere.
+    pub fn boot_image(self, project_root: &Path, release: bool) -> PathBuf {
+        let mode = if release { "release" } else { "debug" };
+        let (target_dir, file) = match self {
+            Self::X86_64 => ("x86_64-veridian", "veridian-uefi.img"),
+            Self::Aarch64 => ("aarch64-unknown-none", "veridian-kernel"),
+            Self::Riscv64 => ("riscv64gc-unknown-none-elf", "veridian-kernel"),
+        };
+        project_root
+            .join("target")
+            .join(target_dir)
+            .join(mode)
+            .join(file)
+    }
+
+    /// QEMU arguments for a headless boot with the serial console on stdio.
+    ///
+    /// Disks are opened with `snapshot=on` so runs never modify the images.
+    pub fn qemu_args(self, opts: &QemuOptions) -> Vec<String> {
+        let image = opts.boot_image.display().to_string();
+        let mut args: Vec<String> = Vec::new();
+        match self {
+            Self::X86_64 => {
+                let ovmf = opts
+                    .ovmf
+                    .as_deref()
+                    .unwrap_or(Path::new(OVMF_CANDIDATES[0]));
+                args.extend(
+                    [
+                        "-machine",
+                        "accel=kvm:tcg",
+                        "-drive",
+                        format!("if=pflash,format=raw,readonly=on,file={}", ovmf.display())
+                            .as_str(),
+                        "-drive",
+                        format!("id=disk0,if=none,format=raw,snapshot=on,file={}", image).as_str(),
+                        "-device",
+                        "ide-hd,drive=disk0",
+                    ]
+                    .map(String::from),
+                );
+                if let Some(rootfs) = &opts.rootfs {
+                    args.extend(
+                        [
+                            "-drive",
+                            format!(
+                                "id=vd0,if=none,format=raw,snapshot=on,file={}",
+                                rootfs.display()
+                            )
+                            .as_str(),
+                            "-device",
+                            "virtio-blk-pci,drive=vd0",
+                        ]
+                        .map(String::from),
+                    );
+                }
+            }
+            Self::Aarch64 => args.extend(
+                [
+                    "-M",
+                    "virt",
+                    "-cpu",
+                    "cortex-a72",
+                    "-kernel",
+                    image.as_str(),
+                ]
+                .map(String::from),
+            ),
+            Self::Riscv64 => args.extend(
+                ["-M", "virt", "-bios", "default", "-kernel", image.as_str()].map(String::from),
+            ),
+        }
+        args.extend(
+            [
+                "-m",
+                opts.memory.as_str(),
+                "-serial",
+                "stdio",
+                "-display",
+                "none",
+                "-monitor",
+                "none",
+                "-no-reboot",
+            ]
+            .map(String::from),
+        );
+        args
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Everything needed to boot one architecture
+#[derive(Debug, Clone)]
+pub struct QemuOptions {
+    pub boot_image: PathBuf,
+    /// OVMF firmware (x86_64 only)
+    pub ovmf: Option<PathBuf>,
+    /// Disk image attached as virtio-blk (x86_64 only)
+    pub rootfs: Option<PathBuf>,
+    pub memory: String,
+}
+
+/// First installed OVMF firmware image, if any.
+pub fn find_ovmf() -> Option<PathBuf> {
+    OVMF_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// Whether `program` can be found on `PATH`.
+pub fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// A running QEMU whose serial console is on its stdin and stdout.
+///
+/// QEMU's stderr is merged into the output so startup errors end up in the
+/// log. The process is killed when the console is dropped.
+pub struct QemuConsole {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    output: Receiver<Vec<u8>>,
+}
+
+impl QemuConsole {
+    pub fn spawn(arch: Arch, opts: &QemuOptions) -> std::io::Result<Self> {
+        let mut child = Command::new(arch.qemu_binary())
+            .args(arch.qemu_args(opts))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let (tx, output) = mpsc::channel();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        for mut stream in [Box::new(stdout) as Box<dyn Read + Send>, Box::new(stderr)] {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                while let Ok(n) = stream.read(&mut buf) {
+                    if n == 0 || tx.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            stdin: child.stdin.take(),
+            child,
+            output,
+        })
+    }
+}
+
+impl Console for QemuConsole {
+    fn send(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| std::io::Error::other("console input closed"))?;
+        stdin.write_all(data)?;
+        stdin.flush()
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Recv {
+        match self.output.recv_timeout(timeout) {
+            Ok(data) => Recv::Data(data),
+            Err(RecvTimeoutError::Timeout) => Recv::Timeout,
+            Err(RecvTimeoutError::Disconnected) => Recv::Closed,
+        }
+    }
+}
+
+impl Drop for QemuConsole {
+    fn drop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(image: &str) -> QemuOptions {
+        QemuOptions {
+            boot_image: PathBuf::from(image),
+            ovmf: Some(PathBuf::from("/fw/OVMF.fd")),
+            rootfs: None,
+            memory: "256M".to_string(),
+        }
+    }
+
+    fn has_pair(args: &[String], flag: &str, value: &str) -> bool {
+        args.windows(2).any(|w| w[0] == flag && w[1] == value)
+    }
+
+    #[test]
+    fn arch_names_round_trip() {
+        for arch in Arch::ALL {
+            assert_eq!(Arch::parse(arch.name()), Some(arch));
+        }
+        assert_eq!(Arch::parse("riscv64gc"), Some(Arch::Riscv64));
+        assert_eq!(Arch::parse("mips"), None);
+    }
+
+    #[test]
+    fn boot_images_follow_build_kernel_layout() {
+        let root = Path::new("/src");
+        assert_eq!(
+            Arch::X86_64.boot_image(root, false),
+            Path::new("/src/target/x86_64-veridian/debug/veridian-uefi.img")
+        );
+        assert_eq!(
+            Arch::Riscv64.boot_image(root, true),
+            Path::new("/src/target/riscv64gc-unknown-none-elf/release/veridian-kernel")
+        );
+    }
+
+    #[test]
+    fn x86_64_boots_uefi_image_from_pflash() {
+        let mut opts = options("/img/uefi.img");
+        opts.rootfs = Some(PathBuf::from("/img/rootfs.img"));
+        let args = Arch::X86_64.qemu_args(&opts);
+        assert!(!args.iter().any(|a| a == "-kernel"));
+        assert!(has_pair(
+            &args,
+            "-drive",
+            "if=pflash,format=raw,readonly=on,file=/fw/OVMF.fd"
+        ));
+        assert!(has_pair(
+            &args,
+            "-drive",
+            "id=disk0,if=none,format=raw,snapshot=on,file=/img/uefi.img"
+        ));
+        assert!(has_pair(&args, "-device", "virtio-blk-pci,drive=vd0"));
+        assert!(has_pair(&args, "-serial", "stdio"));
+    }
+
+    #[test]
+    fn riscv64_boots_kernel_through_opensbi() {
+        let args = Arch::Riscv64.qemu_args(&options("/img/kernel"));
+        assert!(has_pair(&args, "-bios", "default"));
+        assert!(has_pair(&args, "-kernel", "/img/kernel"));
+        assert!(has_pair(&args, "-m", "256M"));
+        assert!(has_pair(&args, "-monitor", "none"));
+    }
+}
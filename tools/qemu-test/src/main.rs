@@ -0,0 +1,279 @@
+//! qemu-test -- Boot VeridianOS in QEMU and run serial console scenarios
+//!
+//! Usage:
+//!   qemu-test [options] [scenario...]
+//!
+//! Scenarios are files, or names of files in `tools/qemu-test/scenarios/`
+//! (default: all of them). Every scenario runs once per selected
+//! architecture on a fresh QEMU. Architectures without QEMU, firmware, or a
+//! built boot image are skipped. The exit status is 1 if any run failed.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
+
+use qemu_test::{
+    find_ovmf, in_path,
+    runner::{self, Outcome},
+    scenario::{Scenario, DEFAULT_TIMEOUT},
+    Arch, QemuConsole, QemuOptions,
+};
+
+/// Lines of console output shown for a failed run
+const TAIL_LINES: usize = 20;
+
+fn print_usage() {
+    eprintln!("Usage: qemu-test [options] [scenario...]");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --arch <arch>       x86_64, aarch64, riscv64 or all (default: all)");
+    eprintln!("  --release           Boot release builds instead of debug builds");
+    eprintln!("  --timeout <secs>    Default step timeout (default: 60)");
+    eprintln!("  --rootfs <image>    Attach a BlockFS image as virtio-blk (x86_64)");
+    eprintln!("  --ovmf <path>       OVMF firmware for x86_64 (default: auto-detect)");
+    eprintln!("  --memory <size>     Guest memory (default: 256M)");
+    eprintln!("  --artifacts <dir>   Where console logs go (default: target/qemu-test)");
+    eprintln!("  --root <dir>        Project root (default: the checkout of this tool)");
+    eprintln!("  --list              List bundled scenarios and exit");
+    eprintln!();
+    eprintln!("Example:");
+    eprintln!("  qemu-test --arch x86_64 boot shell");
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    print_usage();
+    process::exit(2);
+}
+
+fn value(args: &[String], i: usize) -> &str {
+    match args.get(i) {
+        Some(value) => value,
+        None => usage_error(&format!("{} needs a value", args[i - 1])),
+    }
+}
+
+fn scenario_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios")
+}
+
+fn bundled_scenarios() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(scenario_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "scenario"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Resolve a scenario argument: an existing file, or a bundled name.
+fn scenario_path(arg: &str) -> PathBuf {
+    let path = PathBuf::from(arg);
+    if path.is_file() {
+        path
+    } else {
+        scenario_dir().join(format!("{}.scenario", arg))
+    }
+}
+
+fn load(path: &Path, default_timeout: Duration) -> Scenario {
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Error: cannot read {}: {}", path.display(), e);
+        process::exit(2);
+    });
+    Scenario::parse(&name, &text, default_timeout).unwrap_or_else(|e| {
+        eprintln!("Error: {}: {}", path.display(), e);
+        process::exit(2);
+    })
+}
+
+/// Why `arch` cannot run here, if it cannot.
+fn skip_reason(arch: Arch, opts: &QemuOptions) -> Option<String> {
+    if !in_path(arch.qemu_binary()) {
+        return Some(format!("{} not found", arch.qemu_binary()));
+    }
+    if !opts.boot_image.is_file() {
+        return Some(format!("{} not built", opts.boot_image.display()));
+    }
+    if arch == Arch::X86_64 && opts.ovmf.is_none() {
+        return Some("no OVMF firmware".into());
+    }
+    None
+}
+
+fn report_failure(outcome: &Outcome, log: &Path) {
+    let Err(failure) = &outcome.result else {
+        return;
+    };
+    println!("    line {}: {}", failure.line, failure.reason);
+    println!("    log: {}", log.display());
+    let output = String::from_utf8_lossy(&outcome.output);
+    let lines: Vec<&str> = output.lines().collect();
+    for line in &lines[lines.len().saturating_sub(TAIL_LINES)..] {
+        println!("    | {}", line);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut arches = Arch::ALL.to_vec();
+    let mut release = false;
+    let mut default_timeout = DEFAULT_TIMEOUT;
+    let mut rootfs: Option<PathBuf> = None;
+    let mut ovmf: Option<PathBuf> = None;
+    let mut memory = String::from("256M");
+    let mut artifacts: Option<PathBuf> = None;
+    let mut root = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../.."));
+    let mut names: Vec<String> = Vec::new();
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--arch" | "-a" => {
+                i += 1;
+                arches = match value(&args, i) {
+                    "all" => Arch::ALL.to_vec(),
+                    name => match Arch::parse(name) {
+                        Some(arch) => vec![arch],
+                        None => usage_error(&format!("unknown architecture '{}'", name)),
+                    },
+                };
+            }
+            "--release" => release = true,
+            "--timeout" | "-t" => {
+                i += 1;
+                let secs = value(&args, i);
+                default_timeout = secs
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|s| Duration::try_from_secs_f64(s).ok())
+                    .unwrap_or_else(|| usage_error(&format!("invalid timeout '{}'", secs)));
+            }
+            "--rootfs" => {
+                i += 1;
+                rootfs = Some(PathBuf::from(value(&args, i)));
+            }
+            "--ovmf" => {
+                i += 1;
+                ovmf = Some(PathBuf::from(value(&args, i)));
+            }
+            "--memory" | "-m" => {
+                i += 1;
+                memory = value(&args, i).to_string();
+            }
+            "--artifacts" => {
+                i += 1;
+                artifacts = Some(PathBuf::from(value(&args, i)));
+            }
+            "--root" => {
+                i += 1;
+                root = PathBuf::from(value(&args, i));
+            }
+            "--list" => {
+                for path in bundled_scenarios() {
+                    if let Some(stem) = path.file_stem() {
+                        println!("{}", stem.to_string_lossy());
+                    }
+                }
+                return;
+            }
+            "--help" | "-h" => {
+                print_usage();
+                return;
+            }
+            arg if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            name => names.push(name.to_string()),
+        }
+        i += 1;
+    }
+
+    let paths = if names.is_empty() {
+        bundled_scenarios()
+    } else {
+        names.iter().map(|n| scenario_path(n)).collect()
+    };
+    if paths.is_empty() {
+        usage_error("no scenarios found");
+    }
+    let scenarios: Vec<Scenario> = paths.iter().map(|p| load(p, default_timeout)).collect();
+
+    let artifacts = artifacts.unwrap_or_else(|| root.join("target").join("qemu-test"));
+    if let Err(e) = fs::create_dir_all(&artifacts) {
+        eprintln!("Error: cannot create {}: {}", artifacts.display(), e);
+        process::exit(2);
+    }
+    let ovmf = ovmf.or_else(find_ovmf);
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for arch in arches {
+        let opts = QemuOptions {
+            boot_image: arch.boot_image(&root, release),
+            ovmf: ovmf.clone(),
+            rootfs: rootfs.clone().filter(|_| arch == Arch::X86_64),
+            memory: memory.clone(),
+        };
+        let skip = skip_reason(arch, &opts);
+
+        for scenario in scenarios.iter().filter(|s| s.runs_on(arch)) {
+            if let Some(reason) = &skip {
+                println!("[SKIP] {} {}: {}", scenario.name, arch, reason);
+                skipped += 1;
+                continue;
+            }
+
+            let log = artifacts.join(format!("{}-{}.log", scenario.name, arch));
+            let outcome = match QemuConsole::spawn(arch, &opts) {
+                Ok(mut console) => runner::run(scenario, &mut console),
+                Err(e) => {
+                    println!(
+                        "[FAIL] {} {}: cannot start QEMU: {}",
+                        scenario.name, arch, e
+                    );
+                    failed += 1;
+                    continue;
+                }
+            };
+            if let Err(e) = fs::write(&log, &outcome.output) {
+                eprintln!("warning: cannot write {}: {}", log.display(), e);
+            }
+
+            let status = if outcome.result.is_ok() {
+                "PASS"
+            } else {
+                "FAIL"
+            };
+            println!(
+                "[{}] {} {} ({:.1}s)",
+                status,
+                scenario.name,
+                arch,
+                outcome.elapsed.as_secs_f64()
+            );
+            if outcome.result.is_ok() {
+                passed += 1;
+            } else {
+                report_failure(&outcome, &log);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "qemu-test: {} passed, {} failed, {} skipped",
+        passed, failed, skipped
+    );
+    if failed > 0 {
+        process::exit(1);
+    }
+}
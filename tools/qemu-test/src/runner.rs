@@ -0,0 +1,306 @@
+//! Scenario runner
+//!
+//! Plays a [`Scenario`] against a [`Console`]: output is collected as it
+//! arrives, `expect` steps search it from just past the previous match, and
+//! every read is checked for kernel panics and the scenario's `reject`
+//! patterns so a crash fails the run immediately instead of at the next
+//! timeout.
+
+use std::time::{Duration, Instant};
+
+use crate::scenario::{Action, Scenario};
+
+/// Output that fails any scenario as soon as it appears
+pub const FATAL_PATTERNS: &[&str] = &["KERNEL PANIC", "panicked at"];
+
+/// Longest single wait on the console, so rejects are noticed promptly
+const POLL_SLICE: Duration = Duration::from_millis(100);
+
+/// Result of one read from a [`Console`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recv {
+    Data(Vec<u8>),
+    Timeout,
+    /// The other end has gone away (QEMU exited)
+    Closed,
+}
+
+/// A serial console the runner can type into and read from
+pub trait Console {
+    fn send(&mut self, data: &[u8]) -> std::io::Result<()>;
+    /// Wait up to `timeout` for more output.
+    fn recv(&mut self, timeout: Duration) -> Recv;
+}
+
+/// Why a scenario failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Scenario line of the step that was running
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Result of a run, with everything the console printed
+#[derive(Debug)]
+pub struct Outcome {
+    pub result: Result<(), Failure>,
+    pub output: Vec<u8>,
+    pub elapsed: Duration,
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+struct Session<'a> {
+    console: &'a mut dyn Console,
+    output: Vec<u8>,
+    /// End of the last `expect` match
+    cursor: usize,
+    /// Active rejects, with the output offset they apply from
+    rejects: Vec<(String, usize)>,
+    closed: bool,
+}
+
+impl Session<'_> {
+    /// Read output until `deadline`, stopping early once `done` holds.
+    fn pump(
+        &mut self,
+        line: usize,
+        deadline: Instant,
+        mut done: impl FnMut(&Self) -> bool,
+    ) -> Result<bool, Failure> {
+        loop {
+            if done(self) {
+                return Ok(true);
+            }
+            let now = Instant::now();
+            if now >= deadline || self.closed {
+                return Ok(false);
+            }
+            match self.console.recv(POLL_SLICE.min(deadline - now)) {
+                Recv::Data(data) => {
+                    self.output.extend(data.into_iter().filter(|&b| b != b'\r'));
+                    self.check_rejects(line)?;
+                }
+                Recv::Timeout => {}
+                Recv::Closed => self.closed = true,
+            }
+        }
+    }
+
+    fn check_rejects(&self, line: usize) -> Result<(), Failure> {
+        let fatal = FATAL_PATTERNS.iter().map(|p| (*p, 0));
+        let rejects = self.rejects.iter().map(|(p, from)| (p.as_str(), *from));
+        for (pattern, from) in fatal.chain(rejects) {
+            if find(&self.output[from..], pattern.as_bytes()).is_some() {
+                return Err(Failure {
+                    line,
+                    reason: format!("output contains '{}'", pattern),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, line: usize, action: &Action, timeout: Duration) -> Result<(), Failure> {
+        let fail = |reason: String| Failure { line, reason };
+        match action {
+            Action::Expect(text) => {
+                let needle = text.as_bytes();
+                let mut found = None;
+                let matched = self.pump(line, Instant::now() + timeout, |s| {
+                    found = find(&s.output[s.cursor..], needle);
+                    found.is_some()
+                })?;
+                match found {
+                    Some(offset) if matched => {
+                        self.cursor += offset + needle.len();
+                        Ok(())
+                    }
+                    _ if self.closed => Err(fail(format!("QEMU exited before '{}'", text))),
+                    _ => Err(fail(format!(
+                        "no '{}' within {:.1}s",
+                        text,
+                        timeout.as_secs_f64()
+                    ))),
+                }
+            }
+            Action::Send(text) => {
+                if self.closed {
+                    return Err(fail("QEMU exited".into()));
+                }
+                let mut data = text.clone().into_bytes();
+                data.push(b'\n');
+                self.console
+                    .send(&data)
+                    .map_err(|e| fail(format!("cannot write to console: {}", e)))
+            }
+            Action::Reject(text) => {
+                self.rejects.push((text.clone(), self.cursor));
+                self.check_rejects(line)
+            }
+            Action::Sleep(duration) => {
+                self.pump(line, Instant::now() + *duration, |_| false)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Run `scenario` to completion or first failure.
+pub fn run(scenario: &Scenario, console: &mut dyn Console) -> Outcome {
+    let start = Instant::now();
+    let mut session = Session {
+        console,
+        output: Vec::new(),
+        cursor: 0,
+        rejects: Vec::new(),
+        closed: false,
+    };
+    let result = scenario
+        .steps
+        .iter()
+        .try_for_each(|step| session.step(step.line, &step.action, step.timeout));
+    Outcome {
+        result,
+        output: session.output,
+        elapsed: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::scenario::DEFAULT_TIMEOUT;
+
+    /// Console that plays back boot output, then answers each sent line
+    /// with a canned reply.
+    struct FakeConsole {
+        pending: VecDeque<Vec<u8>>,
+        replies: Vec<(&'static str, &'static str)>,
+        sent: Vec<String>,
+        /// Report `Closed` once the pending output runs out
+        exit_when_idle: bool,
+    }
+
+    impl FakeConsole {
+        fn new(boot: &[&str]) -> Self {
+            Self {
+                pending: boot.iter().map(|s| s.as_bytes().to_vec()).collect(),
+                replies: Vec::new(),
+                sent: Vec::new(),
+                exit_when_idle: false,
+            }
+        }
+
+        fn reply(mut self, command: &'static str, output: &'static str) -> Self {
+            self.replies.push((command, output));
+            self
+        }
+    }
+
+    impl Console for FakeConsole {
+        fn send(&mut self, data: &[u8]) -> std::io::Result<()> {
+            let line = String::from_utf8_lossy(data).trim_end().to_string();
+            // Echo like the shell does, then answer.
+            self.pending.push_back(format!("{}\r\n", line).into_bytes());
+            if let Some((_, output)) = self.replies.iter().find(|(cmd, _)| *cmd == line) {
+                self.pending.push_back(output.as_bytes().to_vec());
+            }
+            self.sent.push(line);
+            Ok(())
+        }
+
+        fn recv(&mut self, timeout: Duration) -> Recv {
+            match self.pending.pop_front() {
+                Some(data) => Recv::Data(data),
+                None if self.exit_when_idle => Recv::Closed,
+                None => {
+                    std::thread::sleep(timeout);
+                    Recv::Timeout
+                }
+            }
+        }
+    }
+
+    fn scenario(text: &str) -> Scenario {
+        Scenario::parse("test", text, DEFAULT_TIMEOUT).unwrap()
+    }
+
+    const BOOT: &[&str] = &[
+        "[BOOTSTRAP] Stage 6: User ",
+        "space transition\r\nBOO",
+        "TOK\r\n",
+        "root@veridian:/# ",
+    ];
+
+    #[test]
+    fn matches_output_split_across_reads() {
+        let mut console =
+            FakeConsole::new(BOOT).reply("uname -s", "VeridianOS\r\nroot@veridian:/# ");
+        let outcome = run(
+            &scenario(
+                "expect Stage 6\nexpect BOOTOK\nexpect @veridian:\nsend uname -s\nexpect \
+                 VeridianOS\nexpect @veridian:\n",
+            ),
+            &mut console,
+        );
+        assert_eq!(outcome.result, Ok(()));
+        assert_eq!(console.sent, vec!["uname -s"]);
+        assert!(!outcome.output.contains(&b'\r'));
+    }
+
+    #[test]
+    fn repeated_expect_needs_a_new_occurrence() {
+        let mut console = FakeConsole::new(BOOT);
+        let outcome = run(
+            &scenario("timeout 0.2\nexpect @veridian:\nexpect @veridian:\n"),
+            &mut console,
+        );
+        let failure = outcome.result.unwrap_err();
+        assert_eq!(failure.line, 3);
+        assert_eq!(failure.reason, "no '@veridian:' within 0.2s");
+    }
+
+    #[test]
+    fn reject_fails_on_later_output() {
+        let mut console = FakeConsole::new(BOOT).reply("frob", "vsh: command not found: frob\r\n");
+        let outcome = run(
+            &scenario("expect @veridian:\nreject command not found\nsend frob\nsleep 0.1\n"),
+            &mut console,
+        );
+        let failure = outcome.result.unwrap_err();
+        assert_eq!(failure.line, 4);
+        assert_eq!(failure.reason, "output contains 'command not found'");
+    }
+
+    #[test]
+    fn panic_fails_without_waiting_for_timeout() {
+        let mut console = FakeConsole::new(&["KERNEL PANIC: page fault\r\n"]);
+        let outcome = run(&scenario("expect @veridian:\n"), &mut console);
+        assert_eq!(
+            outcome.result.unwrap_err().reason,
+            "output contains 'KERNEL PANIC'"
+        );
+        assert!(outcome.elapsed < DEFAULT_TIMEOUT);
+    }
+
+    #[test]
+    fn exit_is_reported_before_timeout() {
+        let mut console = FakeConsole::new(&BOOT[..2]);
+        console.exit_when_idle = true;
+        let outcome = run(&scenario("expect Stage 6\nexpect BOOTOK\n"), &mut console);
+        let failure = outcome.result.unwrap_err();
+        assert_eq!(failure.line, 2);
+        assert_eq!(failure.reason, "QEMU exited before 'BOOTOK'");
+        assert_eq!(
+            outcome.output,
+            b"[BOOTSTRAP] Stage 6: User space transition\nBOO".to_vec()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Scenario files
+//!
+//! A scenario is a plain-text script with one directive per line:
+//!
+//! ```text
+//! # comment
+//! arch x86_64 riscv64   architectures to run on (default: all)
+//! timeout 30            seconds each following step may take
+//! expect <text>         wait for <text> after the previous match
+//! send <text>           type <text> and Enter on the serial console
+//! reject <text>         fail if <text> shows up from here on
+//! sleep <seconds>       keep reading output for a while
+//! ```
+//!
+//! Text runs to the end of the line, trimmed. Matching is on plain
+//! substrings of the serial output with carriage returns removed. The shell
+//! echoes what is typed, so an `expect` after a `send` should look for text
+//! only the command's output contains, e.g. `echo $((6 * 7))` and `42`.
+
+use std::{fmt, time::Duration};
+
+use crate::Arch;
+
+/// Step timeout until a `timeout` directive says otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a step does
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Expect(String),
+    Send(String),
+    Reject(String),
+    Sleep(Duration),
+}
+
+/// One directive of a scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// Line number in the scenario file (1-based)
+    pub line: usize,
+    pub action: Action,
+    /// How long an `expect` may wait
+    pub timeout: Duration,
+}
+
+/// A parsed scenario file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    pub arches: Vec<Arch>,
+    pub steps: Vec<Step>,
+}
+
+/// A malformed scenario line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn seconds(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+impl Scenario {
+    /// Parse scenario `text`. Steps before any `timeout` directive get
+    /// `default_timeout`.
+    pub fn parse(name: &str, text: &str, default_timeout: Duration) -> Result<Self, ParseError> {
+        let mut arches = Arch::ALL.to_vec();
+        let mut timeout = default_timeout;
+        let mut steps = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = index + 1;
+            let trimmed = raw.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let (directive, arg) = match trimmed.split_once(char::is_whitespace) {
+                Some((directive, arg)) => (directive, arg.trim()),
+                None => (trimmed, ""),
+            };
+            let error = |message: String| ParseError { line, message };
+            let needs_text = |action: fn(String) -> Action| {
+                if arg.is_empty() {
+                    Err(error(format!("'{}' needs text", directive)))
+                } else {
+                    Ok(action(arg.to_string()))
+                }
+            };
+
+            let action = match directive {
+                "arch" => {
+                    arches = arg
+                        .split_whitespace()
+                        .map(|name| {
+                            Arch::parse(name)
+                                .ok_or_else(|| error(format!("unknown architecture '{}'", name)))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    if arches.is_empty() {
+                        return Err(error("'arch' needs at least one architecture".into()));
+                    }
+                    continue;
+                }
+                "timeout" => {
+                    timeout =
+                        seconds(arg).ok_or_else(|| error(format!("bad timeout '{}'", arg)))?;
+                    continue;
+                }
+                "expect" => needs_text(Action::Expect)?,
+                "reject" => needs_text(Action::Reject)?,
+                // A bare `send` just presses Enter.
+                "send" => Action::Send(arg.to_string()),
+                "sleep" => Action::Sleep(
+                    seconds(arg).ok_or_else(|| error(format!("bad duration '{}'", arg)))?,
+                ),
+                _ => return Err(error(format!("unknown directive '{}'", directive))),
+            };
+            steps.push(Step {
+                line,
+                action,
+                timeout,
+            });
+        }
+
+        if steps.is_empty() {
+            return Err(ParseError {
+                line: 0,
+                message: "scenario has no steps".into(),
+            });
+        }
+        Ok(Self {
+            name: name.to_string(),
+            arches,
+            steps,
+        })
+    }
+
+    pub fn runs_on(&self, arch: Arch) -> bool {
+        self.arches.contains(&arch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Scenario, ParseError> {
+        Scenario::parse("test", text, DEFAULT_TIMEOUT)
+    }
+
+    #[test]
+    fn parses_directives_in_order() {
+        let scenario = parse(
+            "# boot and greet\narch x86_64 riscv64\nexpect BOOTOK\ntimeout 2.5\nsend   echo $((6 \
+             * 7))  \nexpect 42\nreject command not found\nsleep 0.5\nsend\n",
+        )
+        .unwrap();
+
+        assert_eq!(scenario.arches, vec![Arch::X86_64, Arch::Riscv64]);
+        assert!(!scenario.runs_on(Arch::Aarch64));
+        let actions: Vec<_> = scenario.steps.iter().map(|s| s.action.clone()).collect();
+        assert_eq!(
+            actions,
+            vec![
+                Action::Expect("BOOTOK".into()),
+                Action::Send("echo $((6 * 7))".into()),
+                Action::Expect("42".into()),
+                Action::Reject("command not found".into()),
+                Action::Sleep(Duration::from_millis(500)),
+                Action::Send(String::new()),
+            ]
+        );
+        assert_eq!(scenario.steps[0].line, 3);
+        assert_eq!(scenario.steps[0].timeout, DEFAULT_TIMEOUT);
+        assert_eq!(scenario.steps[2].timeout, Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn defaults_to_every_architecture() {
+        let scenario = parse("expect BOOTOK").unwrap();
+        assert!(Arch::ALL.iter().all(|&arch| scenario.runs_on(arch)));
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let cases = [
+            (
+                "expect BOOTOK\nfrobnicate\n",
+                2,
+                "unknown directive 'frobnicate'",
+            ),
+            ("arch x86_64 mips\n", 1, "unknown architecture 'mips'"),
+            ("\n\nexpect\n", 3, "'expect' needs text"),
+            ("timeout soon\n", 1, "bad timeout 'soon'"),
+            ("sleep -1\n", 1, "bad duration '-1'"),
+            ("# nothing\n", 0, "scenario has no steps"),
+        ];
+        for (text, line, message) in cases {
+            let err = parse(text).unwrap_err();
+            assert_eq!(
+                (err.line, err.message.as_str()),
+                (line, message),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn bundled_scenarios_parse() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            if let Err(e) = Scenario::parse("bundled", &text, DEFAULT_TIMEOUT) {
+                panic!("{}: {}", path.display(), e);
+            }
+            count += 1;
+        }
+        assert!(count >= 3);
+    }
+}
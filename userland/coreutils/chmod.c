@@ -0,0 +1,166 @@
+/* chmod.c -- Change file mode bits
+ *
+ * VeridianOS coreutil.  Validates chmod, stat and recursive directory
+ * traversal.
+ *
+ * Usage: chmod [-R] MODE FILE...
+ *   -R  Change directories and their contents recursively.
+ *   MODE is octal (755) or a comma-separated list of symbolic clauses
+ *   [ugoa]*[+-=][rwxXst]* (u+x,go-w).
+ *
+ * Syscalls exercised: stat, chmod, open, getdents64, close
+ */
+
+#include <dirent.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define PATH_BUF 512
+
+static int recursive;
+
+/* Apply MODE_SPEC to OLD.  Returns the new mode, or -1 if invalid. */
+static long apply_mode(const char *spec, mode_t old)
+{
+    if (*spec >= '0' && *spec <= '7') {
+        long m = 0;
+        for (const char *p = spec; *p; p++) {
+            if (*p < '0' || *p > '7')
+                return -1;
+            m = m * 8 + (*p - '0');
+        }
+        return m > 07777 ? -1 : m;
+    }
+
+    mode_t mode = old & 07777;
+    const char *p = spec;
+
+    while (*p) {
+        /* Who: u, g, o, a (none means all) */
+        mode_t who = 0;
+        for (; *p && strchr("ugoa", *p); p++) {
+            switch (*p) {
+            case 'u': who |= S_ISUID | S_IRWXU; break;
+            case 'g': who |= S_ISGID | S_IRWXG; break;
+            case 'o': who |= S_ISVTX | S_IRWXO; break;
+            case 'a': who |= 07777; break;
+            }
+        }
+        if (who == 0)
+            who = 07777;
+
+        if (!*p || !strchr("+-=", *p))
+            return -1;
+
+        while (*p && strchr("+-=", *p)) {
+            char op = *p++;
+            mode_t perm = 0;
+            for (; *p && strchr("rwxXst", *p); p++) {
+                switch (*p) {
+                case 'r': perm |= S_IRUSR | S_IRGRP | S_IROTH; break;
+                case 'w': perm |= S_IWUSR | S_IWGRP | S_IWOTH; break;
+                case 'x': perm |= S_IXUSR | S_IXGRP | S_IXOTH; break;
+                case 'X':
+                    if (S_ISDIR(old) || (old & (S_IXUSR | S_IXGRP | S_IXOTH)))
+                        perm |= S_IXUSR | S_IXGRP | S_IXOTH;
+                    break;
+                case 's': perm |= S_ISUID | S_ISGID; break;
+                case 't': perm |= S_ISVTX; break;
+                }
+            }
+            perm &= who;
+
+            switch (op) {
+            case '+': mode |= perm; break;
+            case '-': mode &= ~perm; break;
+            case '=': mode = (mode & ~who) | perm; break;
+            }
+        }
+
+        if (*p == ',')
+            p++;
+        else if (*p)
+            return -1;
+    }
+
+    return mode;
+}
+
+static int chmod_path(const char *path, const char *spec)
+{
+    struct stat st;
+    if (stat(path, &st) < 0) {
+        perror(path);
+        return 1;
+    }
+
+    long mode = apply_mode(spec, st.st_mode);
+    if (mode < 0) {
+        fprintf(stderr, "chmod: invalid mode: '%s'\n", spec);
+        return 1;
+    }
+
+    int ret = 0;
+    if (chmod(path, (mode_t)mode) < 0) {
+        perror(path);
+        ret = 1;
+    }
+
+    if (recursive && S_ISDIR(st.st_mode)) {
+        DIR *dir = opendir(path);
+        if (!dir) {
+            perror(path);
+            return 1;
+        }
+        struct dirent *de;
+        while ((de = readdir(dir)) != NULL) {
+            if (strcmp(de->d_name, ".") == 0 || strcmp(de->d_name, "..") == 0)
+                continue;
+            char child[PATH_BUF];
+            snprintf(child, PATH_BUF, "%s/%s", path, de->d_name);
+            if (chmod_path(child, spec) != 0)
+                ret = 1;
+        }
+        closedir(dir);
+    }
+
+    return ret;
+}
+
+int main(int argc, char *argv[])
+{
+    int opt;
+
+    while ((opt = getopt(argc, argv, "R")) != -1) {
+        switch (opt) {
+        case 'R':
+            recursive = 1;
+            break;
+        default:
+            write(2, "Usage: chmod [-R] MODE FILE...\n", 31);
+            return 1;
+        }
+    }
+
+    if (argc - optind < 2) {
+        write(2, "Usage: chmod [-R] MODE FILE...\n", 31);
+        return 1;
+    }
+
+    const char *spec = argv[optind];
+    if (apply_mode(spec, 0) < 0) {
+        fprintf(stderr, "chmod: invalid mode: '%s'\n", spec);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind + 1; i < argc; i++) {
+        if (chmod_path(argv[i], spec) != 0)
+            ret = 1;
+    }
+
+    return ret;
+}
@@ -0,0 +1,176 @@
+/* cp.c -- Copy files and directories
+ *
+ * VeridianOS coreutil.  Validates stat, open with O_CREAT/O_TRUNC,
+ * read/write loops, mkdir, opendir/readdir.
+ *
+ * Usage: cp [-r] SOURCE DEST
+ *        cp [-r] SOURCE... DIR
+ *   -r, -R  Copy directories recursively.
+ *
+ * Syscalls exercised: stat, open, read, write, close, mkdir, getdents64
+ */
+
+#include <dirent.h>
+#include <fcntl.h>
+#include <getopt.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define BUF_SIZE 4096
+#define PATH_BUF 512
+
+static int recursive;
+
+static int copy_file(const char *src, const char *dst, mode_t mode)
+{
+    char buf[BUF_SIZE];
+    int n;
+
+    int in = open(src, O_RDONLY);
+    if (in < 0) {
+        perror(src);
+        return 1;
+    }
+    int out = open(dst, O_WRONLY | O_CREAT | O_TRUNC, mode & 07777);
+    if (out < 0) {
+        perror(dst);
+        close(in);
+        return 1;
+    }
+
+    int ret = 0;
+    while ((n = read(in, buf, BUF_SIZE)) > 0) {
+        int written = 0;
+        while (written < n) {
+            int w = write(out, buf + written, n - written);
+            if (w < 0) {
+                perror(dst);
+                ret = 1;
+                goto done;
+            }
+            written += w;
+        }
+    }
+    if (n < 0) {
+        perror(src);
+        ret = 1;
+    }
+
+done:
+    close(in);
+    close(out);
+    return ret;
+}
+
+static int copy_path(const char *src, const char *dst);
+
+static int copy_dir(const char *src, const char *dst, mode_t mode)
+{
+    if (mkdir(dst, mode & 07777) < 0) {
+        struct stat st;
+        if (stat(dst, &st) < 0 || !S_ISDIR(st.st_mode)) {
+            perror(dst);
+            return 1;
+        }
+    }
+
+    DIR *dir = opendir(src);
+    if (!dir) {
+        perror(src);
+        return 1;
+    }
+
+    int ret = 0;
+    struct dirent *de;
+    while ((de = readdir(dir)) != NULL) {
+        if (strcmp(de->d_name, ".") == 0 || strcmp(de->d_name, "..") == 0)
+            continue;
+        char src_path[PATH_BUF], dst_path[PATH_BUF];
+        snprintf(src_path, PATH_BUF, "%s/%s", src, de->d_name);
+        snprintf(dst_path, PATH_BUF, "%s/%s", dst, de->d_name);
+        if (copy_path(src_path, dst_path) != 0)
+            ret = 1;
+    }
+    closedir(dir);
+    return ret;
+}
+
+static int copy_path(const char *src, const char *dst)
+{
+    struct stat st;
+    if (stat(src, &st) < 0) {
+        perror(src);
+        return 1;
+    }
+
+    if (S_ISDIR(st.st_mode)) {
+        if (!recursive) {
+            fprintf(stderr, "cp: -r not specified; omitting directory '%s'\n", src);
+            return 1;
+        }
+        return copy_dir(src, dst, st.st_mode);
+    }
+    return copy_file(src, dst, st.st_mode);
+}
+
+/* Last path component of PATH, ignoring trailing slashes. */
+static void base_name(const char *path, char *buf, int bufsize)
+{
+    int end = strlen(path);
+    while (end > 1 && path[end - 1] == '/')
+        end--;
+    int start = end;
+    while (start > 0 && path[start - 1] != '/')
+        start--;
+    snprintf(buf, bufsize, "%.*s", end - start, path + start);
+}
+
+int main(int argc, char *argv[])
+{
+    int opt;
+
+    while ((opt = getopt(argc, argv, "rR")) != -1) {
+        switch (opt) {
+        case 'r':
+        case 'R':
+            recursive = 1;
+            break;
+        default:
+            write(2, "Usage: cp [-r] SOURCE... DEST\n", 30);
+            return 1;
+        }
+    }
+
+    int nargs = argc - optind;
+    if (nargs < 2) {
+        write(2, "Usage: cp [-r] SOURCE... DEST\n", 30);
+        return 1;
+    }
+
+    const char *dest = argv[argc - 1];
+    struct stat st;
+    int dest_is_dir = stat(dest, &st) == 0 && S_ISDIR(st.st_mode);
+
+    if (nargs > 2 && !dest_is_dir) {
+        fprintf(stderr, "cp: target '%s' is not a directory\n", dest);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind; i < argc - 1; i++) {
+        if (dest_is_dir) {
+            char name[NAME_MAX + 1], path[PATH_BUF];
+            base_name(argv[i], name, sizeof(name));
+            snprintf(path, PATH_BUF, "%s/%s", dest, name);
+            if (copy_path(argv[i], path) != 0)
+                ret = 1;
+        } else if (copy_path(argv[i], dest) != 0) {
+            ret = 1;
+        }
+    }
+
+    return ret;
+}
@@ -0,0 +1,184 @@
+/* grep.c -- Print lines matching a pattern
+ *
+ * VeridianOS coreutil.  Validates regcomp/regexec, getline and buffered
+ * stdio.
+ *
+ * Usage: grep [-EFicvnlqHh] PATTERN [FILE...]
+ *   -E  PATTERN is an extended regular expression.
+ *   -F  PATTERN is a fixed string.
+ *   -i  Ignore case.
+ *   -v  Select non-matching lines.
+ *   -c  Print only a count of selected lines per file.
+ *   -n  Prefix each line with its line number.
+ *   -l  Print only the names of files with selected lines.
+ *   -q  Quiet: exit with status 0 on the first selected line.
+ *   -H  Always prefix lines with the file name.
+ *   -h  Never prefix lines with the file name.
+ *   With no FILE, or when FILE is -, read standard input.
+ *
+ * Exit status: 0 if a line was selected, 1 if none, 2 on error.
+ *
+ * Syscalls exercised: open, read, write, close + regex
+ */
+
+#include <ctype.h>
+#include <getopt.h>
+#include <regex.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+static regex_t regex;
+static const char *fixed;
+static int ignore_case, invert, count_only, line_numbers, files_only, quiet;
+
+/* Case-insensitive substring search for -Fi. */
+static int contains_nocase(const char *hay, const char *needle)
+{
+    size_t n = strlen(needle);
+    for (; *hay; hay++) {
+        size_t i = 0;
+        while (i < n && hay[i] &&
+               tolower((unsigned char)hay[i]) == tolower((unsigned char)needle[i]))
+            i++;
+        if (i == n)
+            return 1;
+    }
+    return n == 0;
+}
+
+static int line_matches(const char *line)
+{
+    int found;
+    if (fixed)
+        found = ignore_case ? contains_nocase(line, fixed)
+                            : strstr(line, fixed) != NULL;
+    else
+        found = regexec(&regex, line, 0, NULL, 0) == 0;
+    return found != invert;
+}
+
+/* Search one stream.  Returns the number of selected lines. */
+static long grep_stream(FILE *fp, const char *name)
+{
+    char *line = NULL;
+    size_t cap = 0;
+    ssize_t len;
+    long lineno = 0, selected = 0;
+
+    while ((len = getline(&line, &cap, fp)) >= 0) {
+        lineno++;
+        if (len > 0 && line[len - 1] == '\n')
+            line[len - 1] = '\0';
+        if (!line_matches(line))
+            continue;
+
+        selected++;
+        if (quiet || files_only)
+            break;
+        if (count_only)
+            continue;
+        if (name)
+            printf("%s:", name);
+        if (line_numbers)
+            printf("%ld:", lineno);
+        printf("%s\n", line);
+    }
+
+    free(line);
+
+    if (files_only && selected > 0)
+        printf("%s\n", name ? name : "(standard input)");
+    else if (count_only && !quiet) {
+        if (name)
+            printf("%s:", name);
+        printf("%ld\n", selected);
+    }
+    return selected;
+}
+
+int main(int argc, char *argv[])
+{
+    int extended = 0, use_fixed = 0;
+    int with_name = -1;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "EFicvnlqHh")) != -1) {
+        switch (opt) {
+        case 'E': extended = 1; break;
+        case 'F': use_fixed = 1; break;
+        case 'i': ignore_case = 1; break;
+        case 'v': invert = 1; break;
+        case 'c': count_only = 1; break;
+        case 'n': line_numbers = 1; break;
+        case 'l': files_only = 1; break;
+        case 'q': quiet = 1; break;
+        case 'H': with_name = 1; break;
+        case 'h': with_name = 0; break;
+        default:
+            write(2, "Usage: grep [-EFicvnlqHh] PATTERN [FILE...]\n", 44);
+            return 2;
+        }
+    }
+
+    if (optind >= argc) {
+        write(2, "Usage: grep [-EFicvnlqHh] PATTERN [FILE...]\n", 44);
+        return 2;
+    }
+
+    const char *pattern = argv[optind++];
+    if (use_fixed) {
+        fixed = pattern;
+    } else {
+        int flags = REG_NOSUB;
+        if (extended)
+            flags |= REG_EXTENDED;
+        if (ignore_case)
+            flags |= REG_ICASE;
+        int rc = regcomp(&regex, pattern, flags);
+        if (rc != 0) {
+            char msg[128];
+            regerror(rc, &regex, msg, sizeof(msg));
+            fprintf(stderr, "grep: %s\n", msg);
+            return 2;
+        }
+    }
+
+    int nfiles = argc - optind;
+    if (with_name < 0)
+        with_name = nfiles > 1;
+
+    long selected = 0;
+    int error = 0;
+
+    if (nfiles == 0) {
+        selected += grep_stream(stdin, with_name ? "(standard input)" : NULL);
+    }
+    for (int i = optind; i < argc && !(quiet && selected > 0); i++) {
+        const char *label = with_name ? argv[i] : NULL;
+        if (strcmp(argv[i], "-") == 0) {
+            selected += grep_stream(stdin, with_name ? "(standard input)" : NULL);
+            continue;
+        }
+        FILE *fp = fopen(argv[i], "r");
+        if (!fp) {
+            if (!quiet)
+                perror(argv[i]);
+            error = 1;
+            continue;
+        }
+        /* -l prints the file name even without -H */
+        selected += grep_stream(fp, files_only ? argv[i] : label);
+        fclose(fp);
+    }
+
+    if (!use_fixed)
+        regfree(&regex);
+
+    if (quiet && selected > 0)
+        return 0;
+    if (error)
+        return 2;
+    return selected > 0 ? 0 : 1;
+}
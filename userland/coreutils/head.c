@@ -0,0 +1,111 @@
+/* head.c -- Output the first part of files
+ *
+ * VeridianOS coreutil.  Validates read/write loops and getopt.
+ *
+ * Usage: head [-n LINES] [-c BYTES] [FILE...]
+ *   -n LINES  Print the first LINES lines (default 10).
+ *   -c BYTES  Print the first BYTES bytes.
+ *   With no FILE, or when FILE is -, read standard input.
+ *
+ * Syscalls exercised: open, read, write, close
+ */
+
+#include <fcntl.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define BUF_SIZE 4096
+
+static int head_fd(int fd, long count, int bytes)
+{
+    char buf[BUF_SIZE];
+    int n = 0;
+
+    while (count > 0 && (n = read(fd, buf, BUF_SIZE)) > 0) {
+        int len = 0;
+        if (bytes) {
+            len = n < count ? n : (int)count;
+            count -= len;
+        } else {
+            while (len < n && count > 0) {
+                if (buf[len++] == '\n')
+                    count--;
+            }
+        }
+        if (write(1, buf, len) != len) {
+            perror("head: write");
+            return 1;
+        }
+    }
+
+    if (n < 0) {
+        perror("head: read");
+        return 1;
+    }
+    return 0;
+}
+
+static long parse_count(const char *arg)
+{
+    char *end;
+    long v = strtol(arg, &end, 10);
+    if (*arg == '\0' || *end != '\0' || v < 0) {
+        fprintf(stderr, "head: invalid number '%s'\n", arg);
+        exit(1);
+    }
+    return v;
+}
+
+int main(int argc, char *argv[])
+{
+    long count = 10;
+    int bytes = 0;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "n:c:")) != -1) {
+        switch (opt) {
+        case 'n':
+            count = parse_count(optarg);
+            bytes = 0;
+            break;
+        case 'c':
+            count = parse_count(optarg);
+            bytes = 1;
+            break;
+        default:
+            write(2, "Usage: head [-n LINES] [-c BYTES] [FILE...]\n", 44);
+            return 1;
+        }
+    }
+
+    int nargs = argc - optind;
+    if (nargs == 0)
+        return head_fd(0, count, bytes);
+
+    int ret = 0;
+    for (int i = optind; i < argc; i++) {
+        if (nargs > 1)
+            printf("%s==> %s <==\n", i > optind ? "\n" : "", argv[i]);
+        fflush(stdout);
+
+        if (strcmp(argv[i], "-") == 0) {
+            if (head_fd(0, count, bytes) != 0)
+                ret = 1;
+            continue;
+        }
+        int fd = open(argv[i], O_RDONLY);
+        if (fd < 0) {
+            perror(argv[i]);
+            ret = 1;
+            continue;
+        }
+        if (head_fd(fd, count, bytes) != 0)
+            ret = 1;
+        close(fd);
+    }
+
+    return ret;
+}
@@ -0,0 +1,97 @@
+/* ln.c -- Make links between files
+ *
+ * VeridianOS coreutil.  Validates link, symlink and unlink.
+ *
+ * Usage: ln [-sf] TARGET LINK_NAME
+ *        ln [-sf] TARGET... DIR
+ *   -s  Make symbolic links instead of hard links.
+ *   -f  Remove existing destination files.
+ *
+ * Syscalls exercised: link, symlink, unlink, stat
+ */
+
+#include <errno.h>
+#include <getopt.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define PATH_BUF 512
+
+static int symbolic;
+static int force;
+
+static int make_link(const char *target, const char *name)
+{
+    if (force)
+        unlink(name);
+
+    int rc = symbolic ? symlink(target, name) : link(target, name);
+    if (rc < 0) {
+        fprintf(stderr, "ln: cannot create link '%s' -> '%s': %s\n",
+                name, target, strerror(errno));
+        return 1;
+    }
+    return 0;
+}
+
+/* Last path component of PATH, ignoring trailing slashes. */
+static void base_name(const char *path, char *buf, int bufsize)
+{
+    int end = strlen(path);
+    while (end > 1 && path[end - 1] == '/')
+        end--;
+    int start = end;
+    while (start > 0 && path[start - 1] != '/')
+        start--;
+    snprintf(buf, bufsize, "%.*s", end - start, path + start);
+}
+
+int main(int argc, char *argv[])
+{
+    int opt;
+
+    while ((opt = getopt(argc, argv, "sf")) != -1) {
+        switch (opt) {
+        case 's': symbolic = 1; break;
+        case 'f': force = 1; break;
+        default:
+            write(2, "Usage: ln [-sf] TARGET... LINK_NAME|DIR\n", 40);
+            return 1;
+        }
+    }
+
+    int nargs = argc - optind;
+    if (nargs < 1) {
+        write(2, "Usage: ln [-sf] TARGET... LINK_NAME|DIR\n", 40);
+        return 1;
+    }
+
+    /* A lone TARGET links into the current directory */
+    const char *dest = nargs == 1 ? "." : argv[argc - 1];
+    int ntargets = nargs == 1 ? 1 : nargs - 1;
+    struct stat st;
+    int dest_is_dir = stat(dest, &st) == 0 && S_ISDIR(st.st_mode);
+
+    if (ntargets > 1 && !dest_is_dir) {
+        fprintf(stderr, "ln: target '%s' is not a directory\n", dest);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind; i < optind + ntargets; i++) {
+        if (dest_is_dir) {
+            char name[NAME_MAX + 1], path[PATH_BUF];
+            base_name(argv[i], name, sizeof(name));
+            snprintf(path, PATH_BUF, "%s/%s", dest, name);
+            if (make_link(argv[i], path) != 0)
+                ret = 1;
+        } else if (make_link(argv[i], dest) != 0) {
+            ret = 1;
+        }
+    }
+
+    return ret;
+}
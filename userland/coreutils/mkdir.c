@@ -0,0 +1,102 @@
+/* mkdir.c -- Make directories
+ *
+ * VeridianOS coreutil.  Validates mkdir, stat and octal mode parsing.
+ *
+ * Usage: mkdir [-p] [-m MODE] DIR...
+ *   -p       Create missing parent directories; an existing directory is
+ *            not an error.
+ *   -m MODE  Permission bits (octal) for the new directories.
+ *
+ * Syscalls exercised: mkdir, stat
+ */
+
+#include <errno.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define PATH_BUF 512
+
+static int make_dir(const char *path, mode_t mode, int parents)
+{
+    if (mkdir(path, mode) == 0)
+        return 0;
+
+    if (parents && errno == EEXIST) {
+        struct stat st;
+        if (stat(path, &st) == 0 && S_ISDIR(st.st_mode))
+            return 0;
+    }
+    fprintf(stderr, "mkdir: cannot create directory '%s': %s\n",
+            path, strerror(errno));
+    return 1;
+}
+
+/* Create every ancestor of PATH, then PATH itself. */
+static int make_parents(const char *path, mode_t mode)
+{
+    char buf[PATH_BUF];
+    int len = strlen(path);
+
+    if (len >= PATH_BUF) {
+        fprintf(stderr, "mkdir: path too long: %s\n", path);
+        return 1;
+    }
+    memcpy(buf, path, len + 1);
+
+    for (int i = 1; i < len; i++) {
+        if (buf[i] != '/' || buf[i - 1] == '/')
+            continue;
+        buf[i] = '\0';
+        if (make_dir(buf, 0777, 1) != 0)
+            return 1;
+        buf[i] = '/';
+    }
+    return make_dir(path, mode, 1);
+}
+
+int main(int argc, char *argv[])
+{
+    int parents = 0;
+    mode_t mode = 0777;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "pm:")) != -1) {
+        switch (opt) {
+        case 'p':
+            parents = 1;
+            break;
+        case 'm': {
+            char *end;
+            unsigned long m = strtoul(optarg, &end, 8);
+            if (*end != '\0' || m > 07777) {
+                fprintf(stderr, "mkdir: invalid mode '%s'\n", optarg);
+                return 1;
+            }
+            mode = (mode_t)m;
+            break;
+        }
+        default:
+            write(2, "Usage: mkdir [-p] [-m MODE] DIR...\n", 35);
+            return 1;
+        }
+    }
+
+    if (optind >= argc) {
+        write(2, "Usage: mkdir [-p] [-m MODE] DIR...\n", 35);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind; i < argc; i++) {
+        int r = parents ? make_parents(argv[i], mode)
+                        : make_dir(argv[i], mode, 0);
+        if (r != 0)
+            ret = 1;
+    }
+
+    return ret;
+}
@@ -0,0 +1,127 @@
+/* mv.c -- Move (rename) files
+ *
+ * VeridianOS coreutil.  Validates rename, with a copy-and-unlink fallback
+ * for regular files when the source and target are on different
+ * filesystems.
+ *
+ * Usage: mv SOURCE DEST
+ *        mv SOURCE... DIR
+ *
+ * Syscalls exercised: rename, stat, open, read, write, unlink
+ */
+
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define BUF_SIZE 4096
+#define PATH_BUF 512
+
+/* Copy SRC to DST and remove SRC (rename across filesystems). */
+static int copy_and_unlink(const char *src, const char *dst)
+{
+    char buf[BUF_SIZE];
+    struct stat st;
+    int n;
+
+    if (stat(src, &st) < 0) {
+        perror(src);
+        return 1;
+    }
+    if (!S_ISREG(st.st_mode)) {
+        fprintf(stderr, "mv: cannot move '%s' across filesystems\n", src);
+        return 1;
+    }
+
+    int in = open(src, O_RDONLY);
+    if (in < 0) {
+        perror(src);
+        return 1;
+    }
+    int out = open(dst, O_WRONLY | O_CREAT | O_TRUNC, st.st_mode & 07777);
+    if (out < 0) {
+        perror(dst);
+        close(in);
+        return 1;
+    }
+
+    int ret = 0;
+    while ((n = read(in, buf, BUF_SIZE)) > 0) {
+        if (write(out, buf, n) != n) {
+            perror(dst);
+            ret = 1;
+            break;
+        }
+    }
+    if (n < 0) {
+        perror(src);
+        ret = 1;
+    }
+    close(in);
+    close(out);
+
+    if (ret == 0 && unlink(src) < 0) {
+        perror(src);
+        ret = 1;
+    }
+    return ret;
+}
+
+static int move(const char *src, const char *dst)
+{
+    if (rename(src, dst) == 0)
+        return 0;
+    if (errno == EXDEV)
+        return copy_and_unlink(src, dst);
+    fprintf(stderr, "mv: cannot move '%s' to '%s': %s\n",
+            src, dst, strerror(errno));
+    return 1;
+}
+
+/* Last path component of PATH, ignoring trailing slashes. */
+static void base_name(const char *path, char *buf, int bufsize)
+{
+    int end = strlen(path);
+    while (end > 1 && path[end - 1] == '/')
+        end--;
+    int start = end;
+    while (start > 0 && path[start - 1] != '/')
+        start--;
+    snprintf(buf, bufsize, "%.*s", end - start, path + start);
+}
+
+int main(int argc, char *argv[])
+{
+    if (argc < 3) {
+        write(2, "Usage: mv SOURCE... DEST\n", 25);
+        return 1;
+    }
+
+    const char *dest = argv[argc - 1];
+    struct stat st;
+    int dest_is_dir = stat(dest, &st) == 0 && S_ISDIR(st.st_mode);
+
+    if (argc > 3 && !dest_is_dir) {
+        fprintf(stderr, "mv: target '%s' is not a directory\n", dest);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = 1; i < argc - 1; i++) {
+        if (dest_is_dir) {
+            char name[NAME_MAX + 1], path[PATH_BUF];
+            base_name(argv[i], name, sizeof(name));
+            snprintf(path, PATH_BUF, "%s/%s", dest, name);
+            if (move(argv[i], path) != 0)
+                ret = 1;
+        } else if (move(argv[i], dest) != 0) {
+            ret = 1;
+        }
+    }
+
+    return ret;
+}
@@ -0,0 +1,107 @@
+/* rm.c -- Remove files and directories
+ *
+ * VeridianOS coreutil.  Validates unlink, rmdir, lstat and recursive
+ * directory traversal.
+ *
+ * Usage: rm [-rf] FILE...
+ *   -r, -R  Remove directories and their contents recursively.
+ *   -f      Ignore nonexistent files; never report a missing operand.
+ *
+ * Syscalls exercised: lstat, unlink, rmdir, open, getdents64, close
+ */
+
+#include <dirent.h>
+#include <errno.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define PATH_BUF 512
+
+static int recursive;
+static int force;
+
+static int remove_path(const char *path)
+{
+    struct stat st;
+    if (lstat(path, &st) < 0) {
+        if (force && errno == ENOENT)
+            return 0;
+        perror(path);
+        return 1;
+    }
+
+    if (!S_ISDIR(st.st_mode)) {
+        if (unlink(path) < 0) {
+            perror(path);
+            return 1;
+        }
+        return 0;
+    }
+
+    if (!recursive) {
+        fprintf(stderr, "rm: cannot remove '%s': Is a directory\n", path);
+        return 1;
+    }
+
+    DIR *dir = opendir(path);
+    if (!dir) {
+        perror(path);
+        return 1;
+    }
+
+    int ret = 0;
+    struct dirent *de;
+    while ((de = readdir(dir)) != NULL) {
+        if (strcmp(de->d_name, ".") == 0 || strcmp(de->d_name, "..") == 0)
+            continue;
+        char child[PATH_BUF];
+        snprintf(child, PATH_BUF, "%s/%s", path, de->d_name);
+        if (remove_path(child) != 0)
+            ret = 1;
+    }
+    closedir(dir);
+
+    if (rmdir(path) < 0) {
+        perror(path);
+        ret = 1;
+    }
+    return ret;
+}
+
+int main(int argc, char *argv[])
+{
+    int opt;
+
+    while ((opt = getopt(argc, argv, "rRf")) != -1) {
+        switch (opt) {
+        case 'r':
+        case 'R':
+            recursive = 1;
+            break;
+        case 'f':
+            force = 1;
+            break;
+        default:
+            write(2, "Usage: rm [-rf] FILE...\n", 24);
+            return 1;
+        }
+    }
+
+    if (optind >= argc) {
+        if (force)
+            return 0;
+        write(2, "Usage: rm [-rf] FILE...\n", 24);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind; i < argc; i++) {
+        if (remove_path(argv[i]) != 0)
+            ret = 1;
+    }
+
+    return ret;
+}
@@ -0,0 +1,136 @@
+/* stat.c -- Display file status
+ *
+ * VeridianOS coreutil.  Validates lstat/stat, readlink, getpwuid/getgrgid
+ * and gmtime.
+ *
+ * Usage: stat [-L] FILE...
+ *   -L  Follow symbolic links.
+ *
+ * Syscalls exercised: stat, lstat, readlink
+ */
+
+#include <getopt.h>
+#include <grp.h>
+#include <pwd.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <time.h>
+#include <unistd.h>
+
+#define PATH_BUF 512
+
+static const char *file_type(mode_t mode)
+{
+    if (S_ISREG(mode))  return "regular file";
+    if (S_ISDIR(mode))  return "directory";
+    if (S_ISLNK(mode))  return "symbolic link";
+    if (S_ISCHR(mode))  return "character special file";
+    if (S_ISBLK(mode))  return "block special file";
+    if (S_ISFIFO(mode)) return "fifo";
+    if (S_ISSOCK(mode)) return "socket";
+    return "unknown";
+}
+
+static void format_permissions(mode_t mode, char *buf)
+{
+    buf[0] = S_ISDIR(mode) ? 'd' :
+             S_ISLNK(mode) ? 'l' :
+             S_ISCHR(mode) ? 'c' :
+             S_ISBLK(mode) ? 'b' :
+             S_ISFIFO(mode) ? 'p' :
+             S_ISSOCK(mode) ? 's' : '-';
+    buf[1] = (mode & S_IRUSR) ? 'r' : '-';
+    buf[2] = (mode & S_IWUSR) ? 'w' : '-';
+    buf[3] = (mode & S_ISUID) ? ((mode & S_IXUSR) ? 's' : 'S') :
+             (mode & S_IXUSR) ? 'x' : '-';
+    buf[4] = (mode & S_IRGRP) ? 'r' : '-';
+    buf[5] = (mode & S_IWGRP) ? 'w' : '-';
+    buf[6] = (mode & S_ISGID) ? ((mode & S_IXGRP) ? 's' : 'S') :
+             (mode & S_IXGRP) ? 'x' : '-';
+    buf[7] = (mode & S_IROTH) ? 'r' : '-';
+    buf[8] = (mode & S_IWOTH) ? 'w' : '-';
+    buf[9] = (mode & S_ISVTX) ? ((mode & S_IXOTH) ? 't' : 'T') :
+             (mode & S_IXOTH) ? 'x' : '-';
+    buf[10] = '\0';
+}
+
+static void print_time(const char *label, const struct timespec *ts)
+{
+    time_t secs = ts->tv_sec;
+    struct tm *tm = gmtime(&secs);
+    if (tm) {
+        printf("%s: %04d-%02d-%02d %02d:%02d:%02d.%09ld +0000\n", label,
+               tm->tm_year + 1900, tm->tm_mon + 1, tm->tm_mday,
+               tm->tm_hour, tm->tm_min, tm->tm_sec, (long)ts->tv_nsec);
+    } else {
+        printf("%s: %ld\n", label, (long)secs);
+    }
+}
+
+static int stat_file(const char *path, int follow)
+{
+    struct stat st;
+    if ((follow ? stat(path, &st) : lstat(path, &st)) < 0) {
+        perror(path);
+        return 1;
+    }
+
+    if (S_ISLNK(st.st_mode)) {
+        char target[PATH_BUF];
+        ssize_t n = readlink(path, target, sizeof(target) - 1);
+        target[n > 0 ? n : 0] = '\0';
+        printf("  File: %s -> %s\n", path, target);
+    } else {
+        printf("  File: %s\n", path);
+    }
+    printf("  Size: %-15ld Blocks: %-10ld IO Block: %-6ld %s\n",
+           (long)st.st_size, (long)st.st_blocks, (long)st.st_blksize,
+           file_type(st.st_mode));
+    printf("Device: %lxh/%lud\tInode: %-11lu Links: %lu\n",
+           (unsigned long)st.st_dev, (unsigned long)st.st_dev,
+           (unsigned long)st.st_ino, (unsigned long)st.st_nlink);
+
+    char perms[12];
+    format_permissions(st.st_mode, perms);
+    struct passwd *pw = getpwuid(st.st_uid);
+    struct group *gr = getgrgid(st.st_gid);
+    printf("Access: (%04o/%s)  Uid: (%5lu/%8s)   Gid: (%5lu/%8s)\n",
+           (unsigned)(st.st_mode & 07777), perms,
+           (unsigned long)st.st_uid, pw ? pw->pw_name : "UNKNOWN",
+           (unsigned long)st.st_gid, gr ? gr->gr_name : "UNKNOWN");
+
+    print_time("Access", &st.st_atim);
+    print_time("Modify", &st.st_mtim);
+    print_time("Change", &st.st_ctim);
+    return 0;
+}
+
+int main(int argc, char *argv[])
+{
+    int follow = 0;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "L")) != -1) {
+        switch (opt) {
+        case 'L':
+            follow = 1;
+            break;
+        default:
+            write(2, "Usage: stat [-L] FILE...\n", 25);
+            return 1;
+        }
+    }
+
+    if (optind >= argc) {
+        write(2, "Usage: stat [-L] FILE...\n", 25);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind; i < argc; i++) {
+        if (stat_file(argv[i], follow) != 0)
+            ret = 1;
+    }
+
+    return ret;
+}
@@ -0,0 +1,168 @@
+/* tail.c -- Output the last part of files
+ *
+ * VeridianOS coreutil.  Validates heap allocation with realloc and
+ * read/write loops.
+ *
+ * Usage: tail [-n [+]LINES] [-c [+]BYTES] [FILE...]
+ *   -n LINES  Print the last LINES lines (default 10); with a leading
+ *             '+', print from line LINES onwards.
+ *   -c BYTES  Print the last BYTES bytes; with a leading '+', print from
+ *             byte BYTES onwards.
+ *   With no FILE, or when FILE is -, read standard input.
+ *
+ * Syscalls exercised: open, read, write, close + malloc/realloc
+ */
+
+#include <fcntl.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define BUF_SIZE 4096
+
+/* Read all of FD into a heap buffer.  Returns NULL on error. */
+static char *slurp(int fd, long *out_len)
+{
+    long cap = BUF_SIZE, len = 0;
+    char *data = malloc(cap);
+    int n;
+
+    if (!data) {
+        write(2, "tail: out of memory\n", 20);
+        return NULL;
+    }
+    while ((n = read(fd, data + len, cap - len)) > 0) {
+        len += n;
+        if (len == cap) {
+            char *grown = realloc(data, cap * 2);
+            if (!grown) {
+                write(2, "tail: out of memory\n", 20);
+                free(data);
+                return NULL;
+            }
+            data = grown;
+            cap *= 2;
+        }
+    }
+    if (n < 0) {
+        perror("tail: read");
+        free(data);
+        return NULL;
+    }
+
+    *out_len = len;
+    return data;
+}
+
+static int tail_fd(int fd, long count, int bytes, int from_start)
+{
+    long len;
+    char *data = slurp(fd, &len);
+    if (!data)
+        return 1;
+
+    long start;
+    if (bytes) {
+        if (from_start)
+            start = count > 0 ? count - 1 : 0;
+        else
+            start = len > count ? len - count : 0;
+    } else if (from_start) {
+        /* Skip the first COUNT - 1 lines */
+        long line = 1;
+        start = 0;
+        while (start < len && line < count) {
+            if (data[start++] == '\n')
+                line++;
+        }
+    } else {
+        /* Walk back over COUNT line ends (a final newline ends the last
+         * line rather than starting a new one) */
+        start = len;
+        if (start > 0 && data[start - 1] == '\n')
+            start--;
+        long seen = 0;
+        while (start > 0) {
+            if (data[start - 1] == '\n' && ++seen == count)
+                break;
+            start--;
+        }
+        if (count == 0)
+            start = len;
+    }
+    if (start > len)
+        start = len;
+
+    int ret = 0;
+    if (write(1, data + start, len - start) != len - start) {
+        perror("tail: write");
+        ret = 1;
+    }
+    free(data);
+    return ret;
+}
+
+static long parse_count(const char *arg, int *from_start)
+{
+    char *end;
+    *from_start = arg[0] == '+';
+    long v = strtol(arg, &end, 10);
+    if (*arg == '\0' || *end != '\0') {
+        fprintf(stderr, "tail: invalid number '%s'\n", arg);
+        exit(1);
+    }
+    return v < 0 ? -v : v;
+}
+
+int main(int argc, char *argv[])
+{
+    long count = 10;
+    int bytes = 0, from_start = 0;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "n:c:")) != -1) {
+        switch (opt) {
+        case 'n':
+            count = parse_count(optarg, &from_start);
+            bytes = 0;
+            break;
+        case 'c':
+            count = parse_count(optarg, &from_start);
+            bytes = 1;
+            break;
+        default:
+            write(2, "Usage: tail [-n [+]LINES] [-c [+]BYTES] [FILE...]\n", 50);
+            return 1;
+        }
+    }
+
+    int nargs = argc - optind;
+    if (nargs == 0)
+        return tail_fd(0, count, bytes, from_start);
+
+    int ret = 0;
+    for (int i = optind; i < argc; i++) {
+        if (nargs > 1)
+            printf("%s==> %s <==\n", i > optind ? "\n" : "", argv[i]);
+        fflush(stdout);
+
+        if (strcmp(argv[i], "-") == 0) {
+            if (tail_fd(0, count, bytes, from_start) != 0)
+                ret = 1;
+            continue;
+        }
+        int fd = open(argv[i], O_RDONLY);
+        if (fd < 0) {
+            perror(argv[i]);
+            ret = 1;
+            continue;
+        }
+        if (tail_fd(fd, count, bytes, from_start) != 0)
+            ret = 1;
+        close(fd);
+    }
+
+    return ret;
+}
@@ -0,0 +1,62 @@
+/* touch.c -- Change file timestamps
+ *
+ * VeridianOS coreutil.  Validates open with O_CREAT and utime.
+ *
+ * Usage: touch [-c] FILE...
+ *   -c  Do not create files that do not exist.
+ *   Existing files get their access and modification times set to now.
+ *
+ * Syscalls exercised: open, close, utimensat
+ */
+
+#include <errno.h>
+#include <fcntl.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <unistd.h>
+#include <utime.h>
+
+int main(int argc, char *argv[])
+{
+    int no_create = 0;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "c")) != -1) {
+        switch (opt) {
+        case 'c':
+            no_create = 1;
+            break;
+        default:
+            write(2, "Usage: touch [-c] FILE...\n", 26);
+            return 1;
+        }
+    }
+
+    if (optind >= argc) {
+        write(2, "Usage: touch [-c] FILE...\n", 26);
+        return 1;
+    }
+
+    int ret = 0;
+    for (int i = optind; i < argc; i++) {
+        if (utime(argv[i], NULL) == 0)
+            continue;
+        if (errno != ENOENT) {
+            perror(argv[i]);
+            ret = 1;
+            continue;
+        }
+        if (no_create)
+            continue;
+
+        int fd = open(argv[i], O_WRONLY | O_CREAT, 0666);
+        if (fd < 0) {
+            perror(argv[i]);
+            ret = 1;
+            continue;
+        }
+        close(fd);
+    }
+
+    return ret;
+}
@@ -0,0 +1,206 @@
+/* tr.c -- Translate, squeeze or delete characters
+ *
+ * VeridianOS coreutil.  Validates byte-wise stdin-to-stdout filtering and
+ * ctype.h character classes.
+ *
+ * Usage: tr [-cds] SET1 [SET2]
+ *   -c  Use the complement of SET1.
+ *   -d  Delete characters in SET1.
+ *   -s  Squeeze repeats of characters in the last set given into one.
+ *   Sets accept ranges (a-z), escapes (\n \t \r \\ \NNN octal) and the
+ *   classes [:alpha:] [:digit:] [:alnum:] [:upper:] [:lower:] [:space:]
+ *   [:punct:] [:blank:].
+ *
+ * Syscalls exercised: read, write
+ */
+
+#include <ctype.h>
+#include <getopt.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+#define BUF_SIZE 4096
+#define SET_MAX  1024
+
+/* Decode one possibly-escaped character at *S, advancing *S past it. */
+static int next_char(const char **s)
+{
+    const char *p = *s;
+    int c = (unsigned char)*p++;
+
+    if (c == '\\' && *p) {
+        c = (unsigned char)*p++;
+        switch (c) {
+        case 'n': c = '\n'; break;
+        case 't': c = '\t'; break;
+        case 'r': c = '\r'; break;
+        case 'a': c = '\a'; break;
+        case 'b': c = '\b'; break;
+        case 'f': c = '\f'; break;
+        case 'v': c = '\v'; break;
+        default:
+            if (c >= '0' && c <= '7') {
+                c -= '0';
+                for (int i = 0; i < 2 && *p >= '0' && *p <= '7'; i++)
+                    c = c * 8 + (*p++ - '0');
+            }
+            break;
+        }
+    }
+    *s = p;
+    return c & 0xff;
+}
+
+static int class_member(const char *name, int c)
+{
+    if (strcmp(name, "alpha") == 0) return isalpha(c);
+    if (strcmp(name, "digit") == 0) return isdigit(c);
+    if (strcmp(name, "alnum") == 0) return isalnum(c);
+    if (strcmp(name, "upper") == 0) return isupper(c);
+    if (strcmp(name, "lower") == 0) return islower(c);
+    if (strcmp(name, "space") == 0) return isspace(c);
+    if (strcmp(name, "punct") == 0) return ispunct(c);
+    if (strcmp(name, "blank") == 0) return c == ' ' || c == '\t';
+    return -1;
+}
+
+/* Expand SPEC into OUT.  Returns the set length, or -1 on error. */
+static int expand_set(const char *spec, unsigned char *out)
+{
+    int len = 0;
+
+    while (*spec) {
+        if (spec[0] == '[' && spec[1] == ':') {
+            const char *end = strstr(spec + 2, ":]");
+            if (end) {
+                char name[16];
+                int n = end - (spec + 2);
+                if (n >= (int)sizeof(name))
+                    n = sizeof(name) - 1;
+                memcpy(name, spec + 2, n);
+                name[n] = '\0';
+                if (class_member(name, 'a') < 0) {
+                    fprintf(stderr, "tr: invalid character class '%s'\n", name);
+                    return -1;
+                }
+                for (int c = 0; c < 256 && len < SET_MAX; c++) {
+                    if (class_member(name, c))
+                        out[len++] = c;
+                }
+                spec = end + 2;
+                continue;
+            }
+        }
+
+        int lo = next_char(&spec);
+        if (spec[0] == '-' && spec[1]) {
+            spec++;
+            int hi = next_char(&spec);
+            if (hi < lo) {
+                fprintf(stderr, "tr: range end before start\n");
+                return -1;
+            }
+            for (int c = lo; c <= hi && len < SET_MAX; c++)
+                out[len++] = c;
+        } else if (len < SET_MAX) {
+            out[len++] = lo;
+        }
+    }
+
+    return len;
+}
+
+int main(int argc, char *argv[])
+{
+    int complement = 0, delete = 0, squeeze = 0;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "cds")) != -1) {
+        switch (opt) {
+        case 'c': complement = 1; break;
+        case 'd': delete = 1; break;
+        case 's': squeeze = 1; break;
+        default:
+            write(2, "Usage: tr [-cds] SET1 [SET2]\n", 29);
+            return 1;
+        }
+    }
+
+    int nsets = argc - optind;
+    if (nsets < 1 || nsets > 2 || (!delete && !squeeze && nsets != 2)) {
+        write(2, "Usage: tr [-cds] SET1 [SET2]\n", 29);
+        return 1;
+    }
+
+    static unsigned char set1[SET_MAX], set2[SET_MAX];
+    int len1 = expand_set(argv[optind], set1);
+    int len2 = nsets == 2 ? expand_set(argv[optind + 1], set2) : 0;
+    if (len1 < 0 || len2 < 0)
+        return 1;
+
+    /* Membership of SET1, complemented with -c */
+    int in_set1[256] = { 0 };
+    for (int i = 0; i < len1; i++)
+        in_set1[set1[i]] = 1;
+    if (complement) {
+        for (int c = 0; c < 256; c++)
+            in_set1[c] = !in_set1[c];
+    }
+
+    /* Translation map: SET1[i] -> SET2[i], the last of SET2 repeating */
+    int map[256];
+    for (int c = 0; c < 256; c++)
+        map[c] = c;
+    if (!delete && len2 > 0) {
+        if (complement) {
+            for (int c = 0; c < 256; c++) {
+                if (in_set1[c])
+                    map[c] = set2[len2 - 1];
+            }
+        } else {
+            for (int i = 0; i < len1; i++)
+                map[set1[i]] = set2[i < len2 ? i : len2 - 1];
+        }
+    }
+
+    /* Squeeze applies to SET2 when translating, to SET1 otherwise */
+    int squeeze_set[256] = { 0 };
+    if (squeeze) {
+        if (nsets == 2) {
+            for (int i = 0; i < len2; i++)
+                squeeze_set[set2[i]] = 1;
+        } else {
+            for (int c = 0; c < 256; c++)
+                squeeze_set[c] = in_set1[c];
+        }
+    }
+
+    unsigned char in[BUF_SIZE], out[BUF_SIZE];
+    int last = -1;
+    int n;
+
+    while ((n = read(0, in, BUF_SIZE)) > 0) {
+        int len = 0;
+        for (int i = 0; i < n; i++) {
+            int c = in[i];
+            if (delete && in_set1[c])
+                continue;
+            c = map[c];
+            if (squeeze && c == last && squeeze_set[c])
+                continue;
+            out[len++] = c;
+            last = c;
+        }
+        if (write(1, out, len) != len) {
+            perror("tr: write");
+            return 1;
+        }
+    }
+    if (n < 0) {
+        perror("tr: read");
+        return 1;
+    }
+
+    return 0;
+}
@@ -0,0 +1,110 @@
+/* uniq.c -- Report or omit repeated lines
+ *
+ * VeridianOS coreutil.  Validates getline, fopen for reading and writing,
+ * and string comparison.
+ *
+ * Usage: uniq [-cdu] [INPUT [OUTPUT]]
+ *   -c  Prefix lines with their number of occurrences.
+ *   -d  Only print duplicated lines, one for each group.
+ *   -u  Only print lines that are not repeated.
+ *   With no INPUT, or when INPUT is -, read standard input.
+ *
+ * Syscalls exercised: open, read, write, close + malloc/realloc
+ */
+
+#include <getopt.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+static int show_count, only_dups, only_unique;
+
+static void emit(FILE *out, const char *line, long count)
+{
+    if (only_dups && count < 2)
+        return;
+    if (only_unique && count > 1)
+        return;
+    if (show_count)
+        fprintf(out, "%7ld %s", count, line);
+    else
+        fputs(line, out);
+}
+
+int main(int argc, char *argv[])
+{
+    int opt;
+
+    while ((opt = getopt(argc, argv, "cdu")) != -1) {
+        switch (opt) {
+        case 'c': show_count = 1; break;
+        case 'd': only_dups = 1; break;
+        case 'u': only_unique = 1; break;
+        default:
+            write(2, "Usage: uniq [-cdu] [INPUT [OUTPUT]]\n", 36);
+            return 1;
+        }
+    }
+
+    FILE *in = stdin, *out = stdout;
+    if (optind < argc && strcmp(argv[optind], "-") != 0) {
+        in = fopen(argv[optind], "r");
+        if (!in) {
+            perror(argv[optind]);
+            return 1;
+        }
+    }
+    if (optind + 1 < argc) {
+        out = fopen(argv[optind + 1], "w");
+        if (!out) {
+            perror(argv[optind + 1]);
+            return 1;
+        }
+    }
+
+    char *prev = NULL, *line = NULL;
+    size_t prev_cap = 0, cap = 0;
+    long count = 0;
+    ssize_t len;
+
+    while ((len = getline(&line, &cap, in)) >= 0) {
+        /* Compare without the newline so a missing final one still
+         * matches */
+        if (len > 0 && line[len - 1] == '\n')
+            line[len - 1] = '\0';
+        if (prev && strcmp(prev, line) == 0) {
+            count++;
+            continue;
+        }
+        if (prev) {
+            strcat(prev, "\n");
+            emit(out, prev, count);
+        }
+        /* Keep a copy with room for the newline re-added on output */
+        size_t need = strlen(line) + 2;
+        if (need > prev_cap) {
+            char *grown = realloc(prev, need);
+            if (!grown) {
+                write(2, "uniq: out of memory\n", 20);
+                return 1;
+            }
+            prev = grown;
+            prev_cap = need;
+        }
+        strcpy(prev, line);
+        count = 1;
+    }
+    if (prev) {
+        strcat(prev, "\n");
+        emit(out, prev, count);
+    }
+
+    free(prev);
+    free(line);
+    if (in != stdin)
+        fclose(in);
+    if (out != stdout)
+        fclose(out);
+    return 0;
+}
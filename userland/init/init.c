@@ -1,20 +1,81 @@
 /*
  * init.c -- PID 1 init process for VeridianOS
  *
- * Minimal init that spawns /bin/sh in a loop. When the shell exits
- * (either normally or due to signal), init respawns it. This ensures
- * the user always has a prompt.
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Init supervises the services defined by the unit files in /etc/init
+ * (see unit.h and kernel/src/services/unit_file.rs):
+ *
+ *   - Enabled units start at boot in dependency order. A unit waits until
+ *     every unit it `requires` is running (starting those too) and every
+ *     unit it is `after` has settled; a failed requirement fails it.
+ *   - A unit of type = "notify" counts as running only once it sends
+ *     INIT_OP_READY (see <veridian/init.h>); otherwise it is killed after
+ *     ready_timeout_ms.
+ *   - A unit with a [health] check must keep sending INIT_OP_HEARTBEAT, or
+ *     answer init's INIT_OP_PING, while it runs; if it falls silent it is
+ *     killed and counted as failed.
+ *   - When a unit exits, its restart policy decides whether it is started
+ *     again, after restart_delay_ms doubling on each consecutive retry.
+ *     Stopping or failing a unit stops the units that require it.
+ *   - A unit that fails for good raises a critical desktop notification
+ *     (SYS_NOTIFY), which is simply dropped when no desktop is running.
+ *   - Once a critical unit has failed for good, init reboots (critical =
+ *     "reboot") or stops everything and starts a rescue shell on the console
+ *     (critical = "rescue", and the fallback when the reboot fails).
+ *   - `vctl` starts, stops and restarts units over the "init" IPC endpoint.
+ *     Unit state is published in /run/init/units.
+ *   - On a power off, reboot or halt request (or a critical unit's reboot)
+ *     init stops every unit, each only once nothing still running requires
+ *     it, then syncs and calls SYS_REBOOT, which unmounts the filesystems.
+ *     The kernel asks for a power off the same way, through
+ *     REBOOT_CMD_PENDING, on a power button press or at a critical
+ *     temperature or battery level.
+ *
+ * A unit's [limits] become its soft and hard resource limits, which the
+ * kernel enforces (see kernel/src/process/rlimit.rs). Its [filter] becomes
+ * a syscall filter that takes effect when the unit's program is exec'd
+ * (see <veridian/syscall_filter.h>).
+ *
+ * Console units (console = true) run as the leader of a fresh session with
+ * the console as controlling terminal, falling back to /bin/sh if their
+ * program is missing. Without any unit files, init runs a built-in console
+ * unit for /bin/login so the user always has a prompt.
+ *
+ * Once no unit is still waiting or starting, init commits the running
+ * kernel's A/B boot slot: reaching this point is the boot-success handshake
+ * that stops a freshly installed kernel from being rolled back. It is never
+ * committed in rescue mode.
+ *
+ * Init arms the kernel watchdog and pets it from its main loop, so the
+ * machine resets if init itself hangs.
  *
  * Cross-compiled by the rootfs build script and installed to /sbin/init.
  */
 
-#include <unistd.h>
-#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
 #include <string.h>
+#include <sys/ioctl.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+#include <veridian/init.h>
+#include <veridian/syscall.h>
+#include <veridian/syscall_filter.h>
 
-/* Default shell and environment */
+#include "unit.h"
+
+/* Fallback shell for console units */
 static const char *shell_path = "/bin/sh";
-static char *const shell_argv[] = { "sh", NULL };
+
+/* Default environment, after each unit's own env entries */
 static char *const shell_envp[] = {
     "PATH=/bin:/usr/bin:/sbin:/usr/sbin",
     "HOME=/",
@@ -27,42 +88,857 @@ static char *const shell_envp[] = {
     "LIBRARY_PATH=/usr/lib:/usr/lib/gcc/x86_64-veridian/14.2.0",
     NULL
 };
+#define SHELL_ENV_COUNT (sizeof(shell_envp) / sizeof(shell_envp[0]) - 1)
+
+/* A unit that ran this long before exiting starts its backoff afresh */
+#define STABLE_RUN_MS       10000
+/* Longest poll() sleep, and the listener respawn delay */
+#define TICK_MS             1000
+/* Kernel watchdog timeout; the main loop pets it at least every TICK_MS */
+#define WATCHDOG_MS         60000
+/* Longest wait for the units to stop before powering off anyway */
+#define SHUTDOWN_MAX_MS     120000
+
+/* One spare slot for the rescue shell */
+static struct unit units[UNIT_MAX + 1];
+static int nunits;
+static int rescue_mode;
+static struct unit *rescue_shell;
+
+/* IPC requests, forwarded by the listener child (see start_listener) */
+static int msg_pipe[2] = { -1, -1 };
+static pid_t listener_pid = -1;
+static long long listener_retry_ms;
 
-/* Write a message to fd 1 (no printf dependency). */
-static void msg(const char *s)
+static int status_dirty;
+static int health_dirty;        /* only check times changed */
+static long long status_written_ms;
+static int boot_committed;
+static int watchdog_armed;
+
+/* REBOOT_CMD_* while shutting down, else 0 */
+static int shutdown_cmd;
+static long long shutdown_deadline_ms;
+
+static long long now_ms(void)
 {
-    write(1, s, strlen(s));
+    struct timespec ts;
+
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return (long long)ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
 }
 
-int main(void)
+static void set_state(struct unit *u, enum unit_state state, const char *reason)
 {
-    pid_t sh;
-    int status;
+    if (u->state != state)
+        printf("[init] %s: %s -> %s%s%s\n", u->name, unit_state_name(u->state),
+               unit_state_name(state), reason && *reason ? ": " : "",
+               reason ? reason : "");
+    u->state = state;
+    if (reason)
+        snprintf(u->reason, sizeof(u->reason), "%s", reason);
+    status_dirty = 1;
+}
+
+/* Whether the unit is wanted up: running or on its way there. */
+static int is_active(const struct unit *u)
+{
+    return u->state == ST_WAITING || u->state == ST_STARTING ||
+           u->state == ST_RUNNING || u->state == ST_BACKOFF;
+}
+
+static int requires(const struct unit *u, const char *name)
+{
+    for (int i = 0; i < u->nrequires; i++)
+        if (strcmp(u->requires[i], name) == 0)
+            return 1;
+    return 0;
+}
+
+/* ========================================================================= */
+/* Starting and stopping                                                     */
+/* ========================================================================= */
+
+/* Want `u` up, pulling in the units it requires. */
+static int want(struct unit *u, int depth)
+{
+    if (u->cyclic)
+        return -ELOOP;
+    if (is_active(u) || depth > UNIT_MAX)
+        return 0;
+    if (u->state == ST_STOPPING) {
+        u->restart_pending = 1;
+        return 0;
+    }
+    u->restarts = 0;
+    set_state(u, ST_WAITING, "");
+    for (int i = 0; i < u->nrequires; i++) {
+        struct unit *dep = unit_find(units, nunits, u->requires[i]);
+        if (dep)
+            want(dep, depth + 1);
+    }
+    return 0;
+}
+
+static void stop_unit(struct unit *u, const char *reason, int depth);
+
+/* Stop the units that require `u`. */
+static void stop_dependents(const struct unit *u, const char *reason, int depth)
+{
+    for (int i = 0; i < nunits; i++)
+        if (is_active(&units[i]) && requires(&units[i], u->name))
+            stop_unit(&units[i], reason, depth + 1);
+}
+
+/* Stop `u` and the units that require it. */
+static void stop_unit(struct unit *u, const char *reason, int depth)
+{
+    if (depth > UNIT_MAX)
+        return;
+    u->restart_pending = 0;
+    stop_dependents(u, "dependency stopped", depth);
+
+    switch (u->state) {
+    case ST_WAITING:
+    case ST_BACKOFF:
+        set_state(u, ST_STOPPED, reason);
+        break;
+    case ST_STARTING:
+    case ST_RUNNING:
+        kill(u->pid, SIGTERM);
+        u->deadline_ms = now_ms() + u->stop_timeout_ms;
+        set_state(u, ST_STOPPING, reason);
+        break;
+    default:
+        break;
+    }
+}
+
+static void escalate(const struct unit *u);
+
+/* Append a notification string: 16-bit little-endian length, then bytes. */
+static size_t put_notify_string(unsigned char *buf, size_t off, const char *s)
+{
+    size_t len = strlen(s);
+
+    buf[off] = (unsigned char)len;
+    buf[off + 1] = (unsigned char)(len >> 8);
+    memcpy(buf + off + 2, s, len);
+    return off + 2 + len;
+}
+
+/* Post "<unit> failed" as a critical desktop notification. */
+static void notify_failure(const struct unit *u, const char *reason)
+{
+    /* header, four length prefixes, "init", summary, reason, action count */
+    unsigned char msg[18 + 4 * 2 + 4 + 2 * 128 + 1];
+    char summary[128];
+    size_t off = 18;
+
+    snprintf(summary, sizeof(summary), "Service %s failed", u->name);
+    memset(msg, 0, off);
+    msg[0] = NOTIFY_MSG_NOTIFY;
+    msg[1] = 2; /* critical */
+    memset(msg + 6, 0xFF, 4); /* urgency's default timeout */
+    off = put_notify_string(msg, off, "init");
+    off = put_notify_string(msg, off, summary);
+    off = put_notify_string(msg, off, strlen(reason) < 128 ? reason : "");
+    off = put_notify_string(msg, off, "");
+    msg[off++] = 0; /* no actions */
+    veridian_syscall2(SYS_NOTIFY, msg, off);
+}
+
+/* Fail `u` for good and stop the units that require it. */
+static void fail_unit(struct unit *u, const char *reason)
+{
+    stop_dependents(u, "dependency failed", 0);
+    set_state(u, ST_FAILED, reason);
+    notify_failure(u, reason);
+    if (u->critical != CRITICAL_NO && !rescue_mode && !shutdown_cmd)
+        escalate(u);
+}
+
+/* Apply the unit's [limits] as both soft and hard limits. */
+static int set_limits(const struct unit *u)
+{
+    const struct {
+        int resource;
+        unsigned value;
+        rlim_t scale;
+    } limits[] = {
+        { RLIMIT_AS,     u->limit_memory_mb,  1024 * 1024 },
+        { RLIMIT_CPU,    u->limit_cpu_s,      1 },
+        { RLIMIT_NOFILE, u->limit_open_files, 1 },
+        { RLIMIT_NPROC,  u->limit_processes,  1 },
+    };
 
-    msg("[init] VeridianOS init started (PID 1)\n");
+    for (size_t i = 0; i < sizeof(limits) / sizeof(limits[0]); i++) {
+        struct rlimit rl;
 
+        if (!limits[i].value)
+            continue;
+        rl.rlim_cur = rl.rlim_max = (rlim_t)limits[i].value * limits[i].scale;
+        if (setrlimit(limits[i].resource, &rl) != 0) {
+            printf("[init] %s: cannot set resource limit %d\n", u->name, limits[i].resource);
+            return -1;
+        }
+    }
+    return 0;
+}
+
+/* Install the unit's [filter], armed for the execve below. */
+static int set_filter(const struct unit *u)
+{
+    long ret;
+
+    if (u->filter_mode < 0)
+        return 0;
+    ret = veridian_syscall5(SYS_SYSCALL_FILTER, 0, u->filter_mode | FILTER_ON_EXEC,
+                            u->filter_action, u->filter, u->nfilter);
+    if (ret < 0) {
+        printf("[init] %s: cannot install syscall filter (error %ld)\n", u->name, -ret);
+        return -1;
+    }
+    return 0;
+}
+
+static void exec_unit(const struct unit *u)
+{
+    char *argv[UNIT_MAX_ARGS + 2];
+    char *envp[UNIT_MAX_ENV + SHELL_ENV_COUNT + 1];
+    const char *slash = strrchr(u->exec, '/');
+    int n = 0;
+
+    argv[n++] = (char *)(slash ? slash + 1 : u->exec);
+    for (int i = 0; i < u->nargs; i++)
+        argv[n++] = (char *)u->args[i];
+    argv[n] = NULL;
+
+    n = 0;
+    for (int i = 0; i < u->nenv; i++)
+        envp[n++] = (char *)u->env[i];
+    for (size_t i = 0; i < SHELL_ENV_COUNT; i++)
+        envp[n++] = shell_envp[i];
+    envp[n] = NULL;
+
+    if (u->console) {
+        /* New session owning the console */
+        setsid();
+        ioctl(0, TIOCSCTTY, 1);
+    }
+    if (chdir(u->dir[0] ? u->dir : "/") != 0)
+        printf("[init] %s: cannot chdir to %s\n", u->name, u->dir);
+    /* Limits first: raising RLIMIT_NPROC needs root */
+    if (set_limits(u) != 0)
+        _exit(126);
+    if (u->group && setgid(u->group) != 0)
+        _exit(126);
+    if (u->user && setuid(u->user) != 0)
+        _exit(126);
+    if (set_filter(u) != 0)
+        _exit(126);
+
+    execve(u->exec, argv, envp);
+    printf("[init] %s: execve(%s) failed\n", u->name, u->exec);
+    if (u->console) {
+        char *shell_argv[] = { "sh", NULL };
+        execve(shell_path, shell_argv, shell_envp);
+        printf("[init] execve(%s) failed\n", shell_path);
+    }
+    _exit(127);
+}
+
+static void schedule_restart(struct unit *u, int failed, const char *reason);
+
+static void spawn(struct unit *u)
+{
+    long long now = now_ms();
+    pid_t pid;
+
+    if (!u->console && access(u->exec, X_OK) != 0) {
+        fail_unit(u, "program not installed");
+        return;
+    }
+    pid = fork();
+
+    if (pid == 0) {
+        close(msg_pipe[0]);
+        close(msg_pipe[1]);
+        exec_unit(u);
+    }
+    if (pid < 0) {
+        schedule_restart(u, 1, "fork failed");
+        return;
+    }
+    u->pid = pid;
+    u->started_ms = now;
+    u->healthy = 1;
+    u->last_ok_ms = now;
+    u->ping_sent_ms = 0;
+    if (u->type == UNIT_NOTIFY) {
+        u->deadline_ms = now + u->ready_timeout_ms;
+        set_state(u, ST_STARTING, "");
+    } else {
+        set_state(u, ST_RUNNING, "");
+    }
+}
+
+/* Start the waiting units whose dependencies have come up. */
+static void start_ready_units(void)
+{
+    int progress = 1;
+
+    while (progress) {
+        progress = 0;
+        for (int i = 0; i < nunits; i++) {
+            struct unit *u = &units[i];
+            char reason[64];
+            int blocked = 0;
+
+            if (u->state != ST_WAITING)
+                continue;
+            for (int d = 0; d < u->nrequires && !blocked; d++) {
+                struct unit *dep = unit_find(units, nunits, u->requires[d]);
+                if (!dep || dep->state == ST_FAILED || dep->state == ST_STOPPED) {
+                    snprintf(reason, sizeof(reason), "dependency %s %s", u->requires[d],
+                             dep ? unit_state_name(dep->state) : "missing");
+                    fail_unit(u, reason);
+                    blocked = progress = 1;
+                } else if (dep->state != ST_RUNNING) {
+                    blocked = 1;
+                }
+            }
+            for (int d = 0; d < u->nafter && !blocked; d++) {
+                struct unit *dep = unit_find(units, nunits, u->after[d]);
+                if (dep && (dep->state == ST_WAITING || dep->state == ST_STARTING ||
+                            dep->state == ST_BACKOFF))
+                    blocked = 1;
+            }
+            if (!blocked) {
+                spawn(u);
+                progress = 1;
+            }
+        }
+    }
+}
+
+/* Apply the restart policy after `u` stopped on its own. */
+static void schedule_restart(struct unit *u, int failed, const char *reason)
+{
+    long long now = now_ms();
+    int again = !shutdown_cmd && (u->restart == RESTART_ALWAYS ||
+                                  (u->restart == RESTART_ON_FAILURE && failed));
+
+    if (u->started_ms && now - u->started_ms >= STABLE_RUN_MS)
+        u->restarts = 0;
+    if (!again) {
+        if (failed) {
+            fail_unit(u, reason);
+        } else {
+            stop_dependents(u, "dependency stopped", 0);
+            set_state(u, ST_STOPPED, reason);
+        }
+        return;
+    }
+    if (u->max_restarts && u->restarts >= u->max_restarts) {
+        fail_unit(u, "too many restarts");
+        return;
+    }
+
+    unsigned shift = u->restarts < 16 ? u->restarts : 16;
+    unsigned long long delay = (unsigned long long)u->restart_delay_ms << shift;
+    if (delay > u->restart_max_delay_ms)
+        delay = u->restart_max_delay_ms;
+    u->restarts++;
+    u->deadline_ms = now + (long long)delay;
+    set_state(u, ST_BACKOFF, reason);
+}
+
+static void unit_exited(struct unit *u, int status)
+{
+    char reason[64];
+    int failed = !(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+    if (u->kill_reason)
+        snprintf(reason, sizeof(reason), "%s", u->kill_reason);
+    else if (WIFSIGNALED(status))
+        snprintf(reason, sizeof(reason), "killed by signal %d", WTERMSIG(status));
+    else
+        snprintf(reason, sizeof(reason), "exited with status %d", WEXITSTATUS(status));
+    u->pid = 0;
+    u->kill_reason = NULL;
+
+    if (u->state == ST_STOPPING) {
+        set_state(u, ST_STOPPED, NULL);
+        if (u->restart_pending) {
+            u->restart_pending = 0;
+            want(u, 0);
+        }
+        return;
+    }
+    schedule_restart(u, failed, reason);
+}
+
+static void send_ping(const struct unit *u)
+{
+    struct init_msg m;
+    long cap = veridian_syscall1(SYS_IPC_LOOKUP_ENDPOINT, u->check_endpoint);
+
+    /* An endpoint that is gone simply never answers */
+    if (cap < 0)
+        return;
+    memset(&m, 0, sizeof(m));
+    m.capability = (uint64_t)cap;
+    m.opcode = INIT_OP_PING;
+    m.flags = (uint32_t)getpid();
+    veridian_syscall4(SYS_IPC_SEND, cap, &m, sizeof(m), 0);
+}
+
+/* When the liveness check of running unit `u` next needs attention. */
+static long long check_deadline(const struct unit *u)
+{
+    if (u->ping_sent_ms)
+        return u->ping_sent_ms + u->check_timeout_ms;
+    if (u->check == CHECK_HEARTBEAT)
+        return u->last_ok_ms + u->check_interval_ms + u->check_timeout_ms;
+    return u->last_ok_ms + u->check_interval_ms;
+}
+
+/* Ping `u` when due, and kill it if it has fallen silent. */
+static void check_liveness(struct unit *u, long long now)
+{
+    if (u->check == CHECK_NONE || u->kill_reason || now < check_deadline(u))
+        return;
+    if (u->check == CHECK_PING && !u->ping_sent_ms) {
+        send_ping(u);
+        u->ping_sent_ms = now;
+        return;
+    }
+
+    printf("[init] %s: liveness check failed\n", u->name);
+    u->healthy = 0;
+    u->check_failures++;
+    u->kill_reason = "liveness check failed";
+    kill(u->pid, SIGKILL);
+    status_dirty = 1;
+}
+
+/* Act on expired ready, restart, stop and liveness deadlines. */
+static void run_timers(void)
+{
+    long long now = now_ms();
+
+    for (int i = 0; i < nunits; i++) {
+        struct unit *u = &units[i];
+        if (u->state == ST_RUNNING)
+            check_liveness(u, now);
+        if (now < u->deadline_ms)
+            continue;
+        switch (u->state) {
+        case ST_STARTING:
+        case ST_STOPPING:
+            /* Not ready in time, or ignoring SIGTERM */
+            if (u->state == ST_STARTING)
+                u->kill_reason = "not ready in time";
+            kill(u->pid, SIGKILL);
+            u->deadline_ms = now + 60 * 60 * 1000;
+            break;
+        case ST_BACKOFF:
+            set_state(u, ST_WAITING, NULL);
+            break;
+        default:
+            break;
+        }
+    }
+}
+
+static int next_timeout(void)
+{
+    long long now = now_ms(), next = now + TICK_MS;
+
+    for (int i = 0; i < nunits; i++) {
+        const struct unit *u = &units[i];
+        if ((u->state == ST_STARTING || u->state == ST_STOPPING || u->state == ST_BACKOFF) &&
+            u->deadline_ms < next)
+            next = u->deadline_ms;
+        if (u->state == ST_RUNNING && u->check != CHECK_NONE && !u->kill_reason &&
+            check_deadline(u) < next)
+            next = check_deadline(u);
+    }
+    return next > now ? (int)(next - now) : 0;
+}
+
+/* ========================================================================= */
+/* Unit table                                                                */
+/* ========================================================================= */
+
+static void load_units(void)
+{
+    nunits = unit_load_dir(INIT_UNIT_DIR, units, nunits);
+    for (int i = 0; i < nunits; i++)
+        if (units[i].cyclic && units[i].state == ST_FAILED)
+            units[i].state = ST_STOPPED;
+    unit_check_cycles(units, nunits);
+    status_dirty = 1;
+}
+
+/* The console login used when /etc/init defines no units. */
+static void add_builtin_console(void)
+{
+    char err[64];
+    static const char console_unit[] =
+        "[unit]\n"
+        "description = \"Console login\"\n"
+        "[service]\n"
+        "exec = \"/bin/login\"\n"
+        "restart = \"always\"\n"
+        "console = true\n";
+
+    if (unit_parse(&units[0], "console", console_unit, err, sizeof(err)) == 0)
+        nunits = 1;
+}
+
+/* Stop every unit and run a root shell on the console. */
+static void enter_rescue(void)
+{
+    char err[64];
+    struct unit *shell = &units[nunits];
+    static const char rescue_unit[] =
+        "[unit]\n"
+        "description = \"Rescue shell\"\n"
+        "[service]\n"
+        "exec = \"/bin/sh\"\n"
+        "restart = \"always\"\n"
+        "console = true\n";
+
+    rescue_mode = 1;
+    for (int i = 0; i < nunits; i++)
+        if (&units[i] != rescue_shell)
+            stop_unit(&units[i], "rescue mode", 0);
+    /* Again, after a failed shutdown */
+    if (rescue_shell) {
+        want(rescue_shell, 0);
+        return;
+    }
+    memset(shell, 0, sizeof(*shell));
+    if (unit_parse(shell, "rescue", rescue_unit, err, sizeof(err)) == 0) {
+        nunits++;
+        rescue_shell = shell;
+        want(shell, 0);
+    }
+}
+
+/* Stop every unit, then reboot, power off or halt (REBOOT_CMD_*). */
+static long begin_shutdown(int cmd, const char *why)
+{
+    if (shutdown_cmd)
+        return -EALREADY;
+    printf("[init] %s, stopping all units\n", why);
+    shutdown_cmd = cmd;
+    shutdown_deadline_ms = now_ms() + SHUTDOWN_MAX_MS;
+    for (int i = 0; i < nunits; i++)
+        units[i].restart_pending = 0;
+    return 0;
+}
+
+/*
+ * Stop the units that no unit still up requires, so each goes down before
+ * the units it needs. Returns 1 once every unit is down.
+ */
+static int shutdown_units(void)
+{
+    int progress = 1;
+
+    while (progress) {
+        progress = 0;
+        for (int i = 0; i < nunits; i++) {
+            struct unit *u = &units[i];
+            int needed = 0;
+
+            if (!is_active(u))
+                continue;
+            for (int j = 0; j < nunits && !needed; j++)
+                needed = (is_active(&units[j]) || units[j].state == ST_STOPPING) &&
+                         requires(&units[j], u->name);
+            if (!needed) {
+                stop_unit(u, "system shutdown", 0);
+                progress = 1;
+            }
+        }
+    }
+    for (int i = 0; i < nunits; i++)
+        if (is_active(&units[i]) || units[i].state == ST_STOPPING)
+            return 0;
+    return 1;
+}
+
+/* The units are down (or out of time): sync and hand over to the kernel. */
+static void finish_shutdown(void)
+{
+    static const char *const action[] = { "", "rebooting", "powering off", "halting" };
+
+    printf("[init] %s\n", action[shutdown_cmd]);
+    if (listener_pid > 0)
+        kill(listener_pid, SIGKILL);
+    sync();
+    veridian_syscall1(SYS_REBOOT, shutdown_cmd);
+
+    printf("[init] %s failed\n", action[shutdown_cmd]);
+    if (shutdown_cmd == REBOOT_CMD_RESTART)
+        veridian_syscall2(SYS_WATCHDOG, WATCHDOG_FIRE, 0);
+    shutdown_cmd = 0;
+    enter_rescue();
+}
+
+/* A critical unit failed for good: reboot, or drop to a rescue shell. */
+static void escalate(const struct unit *u)
+{
+    char why[64];
+
+    if (u->critical == CRITICAL_REBOOT) {
+        snprintf(why, sizeof(why), "critical unit %s failed, rebooting", u->name);
+        begin_shutdown(REBOOT_CMD_RESTART, why);
+        return;
+    }
+    printf("[init] critical unit %s failed, entering rescue mode\n", u->name);
+    enter_rescue();
+}
+
+static void write_status(void)
+{
+    static const char tmp[] = INIT_STATUS_FILE ".tmp";
+    int fd;
+
+    mkdir("/run", 0755);
+    mkdir("/run/init", 0755);
+    fd = open(tmp, O_WRONLY | O_CREAT | O_TRUNC, 0644);
+    if (fd < 0)
+        return;
+    for (int i = 0; i < nunits; i++) {
+        static const char *const critical[] = { "no", "reboot", "rescue" };
+        const struct unit *u = &units[i];
+        const char *health = u->check == CHECK_NONE ? "-" : u->healthy ? "ok" : "failed";
+        dprintf(fd, "%s\t%s\t%d\t%u\t%s\t%u\t%lld\t%s\t%s\t%s\n", u->name,
+                unit_state_name(u->state), (int)u->pid, u->restarts, health,
+                u->check_failures, u->last_ok_ms, critical[u->critical], u->reason,
+                u->description);
+    }
+    close(fd);
+    rename(tmp, INIT_STATUS_FILE);
+    status_dirty = health_dirty = 0;
+    status_written_ms = now_ms();
+}
+
+/* ========================================================================= */
+/* IPC                                                                       */
+/* ========================================================================= */
+
+/*
+ * IPC receive blocks, so a child process owns the "init" endpoint and
+ * forwards each request down a pipe that the main loop polls.
+ */
+static void start_listener(void)
+{
+    pid_t pid = fork();
+
+    if (pid != 0) {
+        listener_pid = pid;
+        listener_retry_ms = now_ms() + TICK_MS;
+        return;
+    }
+
+    long cap = veridian_syscall1(SYS_IPC_CREATE_ENDPOINT, 0);
+    if (cap < 0 || veridian_syscall2(SYS_IPC_BIND_ENDPOINT, cap, INIT_SERVICE) < 0) {
+        printf("[init] cannot bind the \"%s\" endpoint\n", INIT_SERVICE);
+        _exit(1);
+    }
     for (;;) {
-        sh = fork();
-        if (sh < 0) {
-            msg("[init] fork() failed, retrying in 1s\n");
-            /* Simple busy-wait since we don't have sleep() yet */
-            volatile int i;
-            for (i = 0; i < 10000000; i++)
-                ;
+        static uint8_t buf[256];
+        struct init_msg m;
+        long n = veridian_syscall2(SYS_IPC_RECEIVE, cap, buf);
+        if (n < (long)sizeof(m))
             continue;
+        memcpy(&m, buf, sizeof(m));
+        if (write(msg_pipe[1], &m, sizeof(m)) != (ssize_t)sizeof(m))
+            _exit(1);
+    }
+}
+
+static void reply(const struct init_msg *req, long status)
+{
+    char name[sizeof(INIT_REPLY_PREFIX) + 12];
+    struct init_msg m;
+    long cap;
+
+    snprintf(name, sizeof(name), INIT_REPLY_PREFIX "%u", req->flags);
+    cap = veridian_syscall1(SYS_IPC_LOOKUP_ENDPOINT, name);
+    if (cap < 0)
+        return;
+    memset(&m, 0, sizeof(m));
+    m.capability = (uint64_t)cap;
+    m.opcode = req->opcode;
+    m.data[0] = (uint64_t)status;
+    veridian_syscall4(SYS_IPC_SEND, cap, &m, sizeof(m), 0);
+}
+
+static long handle_request(const struct init_msg *m)
+{
+    char name[INIT_NAME_MAX + 1];
+    struct unit *u;
+
+    switch (m->opcode) {
+    case INIT_OP_POWEROFF:
+        return begin_shutdown(REBOOT_CMD_POWER_OFF, "power off requested");
+    case INIT_OP_REBOOT:
+        return begin_shutdown(REBOOT_CMD_RESTART, "reboot requested");
+    case INIT_OP_HALT:
+        return begin_shutdown(REBOOT_CMD_HALT, "halt requested");
+    case INIT_OP_SUSPEND:
+        if (shutdown_cmd)
+            return -EBUSY;
+        sync();
+        return veridian_syscall1(SYS_REBOOT, REBOOT_CMD_SUSPEND);
+    }
+    if (shutdown_cmd)
+        return -EBUSY;
+
+    if (m->opcode == INIT_OP_RELOAD) {
+        load_units();
+        return 0;
+    }
+
+    memcpy(name, m->data, INIT_NAME_MAX);
+    name[INIT_NAME_MAX] = '\0';
+    u = unit_find(units, nunits, name);
+    if (!u)
+        return -ENOENT;
+
+    switch (m->opcode) {
+    case INIT_OP_START:
+        return want(u, 0);
+    case INIT_OP_STOP:
+        stop_unit(u, "stopped by request", 0);
+        return 0;
+    case INIT_OP_RESTART:
+        if (u->state == ST_STARTING || u->state == ST_RUNNING) {
+            stop_unit(u, "restarted by request", 0);
+            u->restart_pending = 1;
+            return 0;
         }
+        return want(u, 0);
+    default:
+        return -ENOSYS;
+    }
+}
+
+static void read_requests(void)
+{
+    struct init_msg m;
+
+    while (read(msg_pipe[0], &m, sizeof(m)) == (ssize_t)sizeof(m)) {
+        if (m.opcode == INIT_OP_READY || m.opcode == INIT_OP_HEARTBEAT) {
+            for (int i = 0; i < nunits; i++) {
+                struct unit *u = &units[i];
+                if ((u->state != ST_STARTING && u->state != ST_RUNNING) ||
+                    u->pid != (pid_t)m.data[0])
+                    continue;
+                u->last_ok_ms = now_ms();
+                u->ping_sent_ms = 0;
+                if (!u->healthy)
+                    status_dirty = 1;
+                u->healthy = 1;
+                health_dirty = 1;
+                if (m.opcode == INIT_OP_READY && u->state == ST_STARTING)
+                    set_state(u, ST_RUNNING, "");
+            }
+            continue;
+        }
+        reply(&m, handle_request(&m));
+    }
+}
+
+/* ========================================================================= */
+/* Main loop                                                                 */
+/* ========================================================================= */
+
+static void reap_children(void)
+{
+    pid_t pid;
+    int status;
+
+    while ((pid = waitpid(-1, &status, WNOHANG)) > 0) {
+        if (pid == listener_pid) {
+            listener_pid = -1;
+            continue;
+        }
+        for (int i = 0; i < nunits; i++) {
+            if (units[i].pid == pid && units[i].state != ST_STOPPED) {
+                unit_exited(&units[i], status);
+                break;
+            }
+        }
+    }
+}
+
+static void commit_boot(void)
+{
+    if (rescue_mode)
+        return;
+    for (int i = 0; i < nunits; i++)
+        if (units[i].state == ST_WAITING || units[i].state == ST_STARTING)
+            return;
+    boot_committed = 1;
+    if (veridian_syscall0(SYS_BOOT_SLOT_COMMIT) == 1)
+        printf("[init] committed the trial kernel boot slot\n");
+}
+
+int main(void)
+{
+    printf("[init] VeridianOS init started (PID 1)\n");
+
+    if (pipe(msg_pipe) != 0)
+        printf("[init] pipe() failed, vctl requests disabled\n");
+    else
+        fcntl(msg_pipe[0], F_SETFL, O_NONBLOCK);
+
+    load_units();
+    if (nunits == 0) {
+        printf("[init] no units in %s, starting a console login\n", INIT_UNIT_DIR);
+        add_builtin_console();
+    }
+    for (int i = 0; i < nunits; i++)
+        if (units[i].enabled)
+            want(&units[i], 0);
+
+    if (veridian_syscall2(SYS_WATCHDOG, WATCHDOG_ARM, WATCHDOG_MS) == 0)
+        watchdog_armed = 1;
+
+    for (;;) {
+        struct pollfd pfd = { .fd = msg_pipe[0], .events = POLLIN };
+
+        if (listener_pid < 0 && msg_pipe[0] >= 0 && now_ms() >= listener_retry_ms)
+            start_listener();
+        reap_children();
+        run_timers();
+        if (!shutdown_cmd) {
+            long req = veridian_syscall1(SYS_REBOOT, REBOOT_CMD_PENDING);
 
-        if (sh == 0) {
-            /* Child: exec the shell */
-            execve(shell_path, shell_argv, shell_envp);
-            /* execve only returns on failure */
-            msg("[init] execve(/bin/sh) failed\n");
-            _exit(127);
+            if (req > 0 && req != REBOOT_CMD_SUSPEND)
+                begin_shutdown((int)req, "power event from the kernel");
         }
+        if (!shutdown_cmd)
+            start_ready_units();
+        else if (shutdown_units() || now_ms() >= shutdown_deadline_ms)
+            finish_shutdown();
+        if (!boot_committed && !shutdown_cmd)
+            commit_boot();
+        if (status_dirty || (health_dirty && now_ms() - status_written_ms >= TICK_MS))
+            write_status();
+        if (watchdog_armed)
+            veridian_syscall1(SYS_WATCHDOG, WATCHDOG_PET);
 
-        /* Parent: wait for child to exit */
-        waitpid(sh, &status, 0);
-        msg("[init] shell exited, respawning\n");
+        if (poll(&pfd, 1, next_timeout()) > 0)
+            read_requests();
     }
 
     /* unreachable */
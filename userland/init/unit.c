@@ -0,0 +1,553 @@
+/*
+ * unit.c -- unit file parsing and dependency checks for init
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Unit files use a small subset of TOML: [sections], "strings" with \" \\
+ * \n \t escapes, integers (with optional _ separators), true/false and
+ * single-line arrays of strings. Unknown keys are ignored.
+ *
+ * A [filter] entry is a syscall group ("@basic", see
+ * <veridian/syscall_filter.h>) or a syscall number ("84").
+ */
+
+#include <dirent.h>
+#include <fcntl.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/syscall_filter.h>
+
+#include "unit.h"
+
+#define UNIT_FILE_MAX   8192
+
+static const char *skip_ws(const char *p)
+{
+    while (*p == ' ' || *p == '\t')
+        p++;
+    return p;
+}
+
+/* Nothing but a comment may follow a value. */
+static int at_end(const char *p)
+{
+    p = skip_ws(p);
+    return *p == '\0' || *p == '#';
+}
+
+/* Parse a "string" at *p into out; advances *p past it. */
+static int parse_string(const char **p, char *out, size_t cap)
+{
+    const char *s = *p;
+    size_t n = 0;
+
+    if (*s++ != '"')
+        return -1;
+    for (; *s && *s != '"'; s++) {
+        char c = *s;
+        if (c == '\\') {
+            switch (*++s) {
+            case 'n':  c = '\n'; break;
+            case 't':  c = '\t'; break;
+            case '"':  c = '"'; break;
+            case '\\': c = '\\'; break;
+            default:   return -1;
+            }
+        }
+        if (n + 1 >= cap)
+            return -1;
+        out[n++] = c;
+    }
+    if (*s != '"')
+        return -1;
+    out[n] = '\0';
+    *p = s + 1;
+    return 0;
+}
+
+/* Parse a ["a", "b"] array into rows of `width` bytes starting at out. */
+static int parse_list(const char *p, char *out, size_t width, int max, int *count)
+{
+    if (*p++ != '[')
+        return -1;
+    *count = 0;
+    for (;;) {
+        p = skip_ws(p);
+        if (*p == ']')
+            return at_end(p + 1) ? 0 : -1;
+        if (*count == max || parse_string(&p, out + (size_t)*count * width, width) != 0)
+            return -1;
+        (*count)++;
+        p = skip_ws(p);
+        if (*p == ',')
+            p++;
+        else if (*p != ']')
+            return -1;
+    }
+}
+
+static int parse_uint(const char *p, unsigned *out)
+{
+    unsigned long long v = 0;
+    int digits = 0;
+
+    for (; *p >= '0' && *p <= '9'; p++) {
+        v = v * 10 + (unsigned)(*p - '0');
+        if (v > 0xFFFFFFFFull)
+            return -1;
+        digits++;
+        if (p[1] == '_' && p[2] >= '0' && p[2] <= '9')
+            p++;
+    }
+    if (!digits || !at_end(p))
+        return -1;
+    *out = (unsigned)v;
+    return 0;
+}
+
+static int parse_bool(const char *p, int *out)
+{
+    if (strncmp(p, "true", 4) == 0 && at_end(p + 4))
+        *out = 1;
+    else if (strncmp(p, "false", 5) == 0 && at_end(p + 5))
+        *out = 0;
+    else
+        return -1;
+    return 0;
+}
+
+static int parse_word(const char *p, char *out, size_t cap)
+{
+    return parse_string(&p, out, cap) == 0 && at_end(p) ? 0 : -1;
+}
+
+int unit_name_valid(const char *name)
+{
+    size_t len = strlen(name);
+
+    if (len == 0 || len > INIT_NAME_MAX)
+        return 0;
+    for (; *name; name++) {
+        char c = *name;
+        if (!((c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') ||
+              (c >= '0' && c <= '9') || c == '-' || c == '_' || c == '.' || c == '@'))
+            return 0;
+    }
+    return 1;
+}
+
+static int check_deps(char deps[][INIT_NAME_MAX + 1], int n)
+{
+    for (int i = 0; i < n; i++)
+        if (!unit_name_valid(deps[i]))
+            return -1;
+    return 0;
+}
+
+static const struct {
+    const char *name;
+    uint32_t entry;
+} filter_groups[] = {
+    { "basic",       FILTER_GROUP_BASIC },
+    { "thread",      FILTER_GROUP_THREAD },
+    { "process",     FILTER_GROUP_PROCESS },
+    { "credentials", FILTER_GROUP_CREDENTIALS },
+    { "file",        FILTER_GROUP_FILE },
+    { "ipc",         FILTER_GROUP_IPC },
+    { "net",         FILTER_GROUP_NET },
+    { "pty",         FILTER_GROUP_PTY },
+    { "crypto",      FILTER_GROUP_CRYPTO },
+    { "graphics",    FILTER_GROUP_GRAPHICS },
+    { "debug",       FILTER_GROUP_DEBUG },
+    { "packages",    FILTER_GROUP_PACKAGES },
+    { "reboot",      FILTER_GROUP_REBOOT },
+    { "watchdog",    FILTER_GROUP_WATCHDOG },
+    { "admin",       FILTER_GROUP_ADMIN },
+};
+
+/* Parse an allow or deny list of groups and syscall numbers. */
+static int parse_filter(struct unit *u, int mode, const char *v)
+{
+    char entries[UNIT_MAX_FILTER][24];
+    int count;
+
+    /* Either an allowlist or a denylist */
+    if (u->filter_mode >= 0 ||
+        parse_list(v, entries[0], sizeof(entries[0]), UNIT_MAX_FILTER, &count) != 0)
+        return -1;
+    for (int i = 0; i < count; i++) {
+        unsigned nr;
+        size_t g;
+
+        if (entries[i][0] != '@') {
+            if (parse_uint(entries[i], &nr) != 0)
+                return -1;
+            u->filter[i] = nr;
+            continue;
+        }
+        for (g = 0; g < sizeof(filter_groups) / sizeof(filter_groups[0]); g++)
+            if (strcmp(entries[i] + 1, filter_groups[g].name) == 0)
+                break;
+        if (g == sizeof(filter_groups) / sizeof(filter_groups[0]))
+            return -1;
+        u->filter[i] = filter_groups[g].entry;
+    }
+    u->nfilter = count;
+    u->filter_mode = mode;
+    return 0;
+}
+
+/* Apply `key = value` in `section`. Returns 0, or -1 for a bad value. */
+static int set_key(struct unit *u, const char *section, const char *key, const char *v)
+{
+    char word[32];
+    unsigned n;
+
+    if (strcmp(section, "health") == 0) {
+        if (strcmp(key, "check") == 0) {
+            if (parse_word(v, word, sizeof(word)) != 0)
+                return -1;
+            if (strcmp(word, "none") == 0)
+                u->check = CHECK_NONE;
+            else if (strcmp(word, "heartbeat") == 0)
+                u->check = CHECK_HEARTBEAT;
+            else if (strcmp(word, "ping") == 0)
+                u->check = CHECK_PING;
+            else
+                return -1;
+            return 0;
+        }
+        if (strcmp(key, "endpoint") == 0)
+            return parse_word(v, u->check_endpoint, sizeof(u->check_endpoint));
+        if (strcmp(key, "interval_ms") == 0)
+            return parse_uint(v, &u->check_interval_ms) == 0 && u->check_interval_ms ? 0 : -1;
+        if (strcmp(key, "timeout_ms") == 0)
+            return parse_uint(v, &u->check_timeout_ms);
+        return 0;
+    }
+    if (strcmp(section, "limits") == 0) {
+        static const struct {
+            const char *key;
+            size_t offset;
+        } limits[] = {
+            { "memory_mb",  offsetof(struct unit, limit_memory_mb) },
+            { "cpu_s",      offsetof(struct unit, limit_cpu_s) },
+            { "open_files", offsetof(struct unit, limit_open_files) },
+            { "processes",  offsetof(struct unit, limit_processes) },
+        };
+        for (size_t i = 0; i < sizeof(limits) / sizeof(limits[0]); i++)
+            if (strcmp(key, limits[i].key) == 0)
+                return parse_uint(v, (unsigned *)((char *)u + limits[i].offset));
+        return 0;
+    }
+    if (strcmp(section, "filter") == 0) {
+        if (strcmp(key, "allow") == 0)
+            return parse_filter(u, FILTER_ALLOW, v);
+        if (strcmp(key, "deny") == 0)
+            return parse_filter(u, FILTER_DENY, v);
+        if (strcmp(key, "action") == 0) {
+            if (parse_word(v, word, sizeof(word)) != 0)
+                return -1;
+            if (strcmp(word, "kill") == 0)
+                u->filter_action = FILTER_KILL;
+            else if (strcmp(word, "errno") == 0)
+                u->filter_action = FILTER_ERRNO;
+            else if (strcmp(word, "log") == 0)
+                u->filter_action = FILTER_LOG;
+            else
+                return -1;
+            return 0;
+        }
+        return 0;
+    }
+    if (strcmp(section, "unit") == 0) {
+        if (strcmp(key, "description") == 0)
+            return parse_word(v, u->description, sizeof(u->description));
+        if (strcmp(key, "requires") == 0)
+            return parse_list(v, u->requires[0], sizeof(u->requires[0]), UNIT_MAX_DEPS,
+                              &u->nrequires) == 0 ? check_deps(u->requires, u->nrequires) : -1;
+        if (strcmp(key, "after") == 0)
+            return parse_list(v, u->after[0], sizeof(u->after[0]), UNIT_MAX_DEPS,
+                              &u->nafter) == 0 ? check_deps(u->after, u->nafter) : -1;
+        if (strcmp(key, "enabled") == 0)
+            return parse_bool(v, &u->enabled);
+        if (strcmp(key, "critical") == 0) {
+            if (parse_word(v, word, sizeof(word)) != 0)
+                return -1;
+            if (strcmp(word, "no") == 0)
+                u->critical = CRITICAL_NO;
+            else if (strcmp(word, "reboot") == 0)
+                u->critical = CRITICAL_REBOOT;
+            else if (strcmp(word, "rescue") == 0)
+                u->critical = CRITICAL_RESCUE;
+            else
+                return -1;
+            return 0;
+        }
+        return 0;
+    }
+    if (strcmp(section, "service") != 0)
+        return 0;
+
+    if (strcmp(key, "exec") == 0)
+        return parse_word(v, u->exec, sizeof(u->exec));
+    if (strcmp(key, "args") == 0)
+        return parse_list(v, u->args[0], sizeof(u->args[0]), UNIT_MAX_ARGS, &u->nargs);
+    if (strcmp(key, "env") == 0)
+        return parse_list(v, u->env[0], sizeof(u->env[0]), UNIT_MAX_ENV, &u->nenv);
+    if (strcmp(key, "dir") == 0)
+        return parse_word(v, u->dir, sizeof(u->dir));
+    if (strcmp(key, "console") == 0)
+        return parse_bool(v, &u->console);
+    if (strcmp(key, "type") == 0) {
+        if (parse_word(v, word, sizeof(word)) != 0)
+            return -1;
+        if (strcmp(word, "simple") == 0)
+            u->type = UNIT_SIMPLE;
+        else if (strcmp(word, "notify") == 0)
+            u->type = UNIT_NOTIFY;
+        else
+            return -1;
+        return 0;
+    }
+    if (strcmp(key, "restart") == 0) {
+        if (parse_word(v, word, sizeof(word)) != 0)
+            return -1;
+        if (strcmp(word, "no") == 0)
+            u->restart = RESTART_NO;
+        else if (strcmp(word, "on-failure") == 0)
+            u->restart = RESTART_ON_FAILURE;
+        else if (strcmp(word, "always") == 0)
+            u->restart = RESTART_ALWAYS;
+        else
+            return -1;
+        return 0;
+    }
+
+    static const struct {
+        const char *key;
+        size_t offset;
+    } uints[] = {
+        { "restart_delay_ms",     offsetof(struct unit, restart_delay_ms) },
+        { "restart_max_delay_ms", offsetof(struct unit, restart_max_delay_ms) },
+        { "max_restarts",         offsetof(struct unit, max_restarts) },
+        { "ready_timeout_ms",     offsetof(struct unit, ready_timeout_ms) },
+    };
+    for (size_t i = 0; i < sizeof(uints) / sizeof(uints[0]); i++)
+        if (strcmp(key, uints[i].key) == 0)
+            return parse_uint(v, (unsigned *)((char *)u + uints[i].offset));
+    if (strcmp(key, "stop_timeout_s") == 0) {
+        if (parse_uint(v, &n) != 0 || n > 3600)
+            return -1;
+        u->stop_timeout_ms = n * 1000;
+        return 0;
+    }
+    if (strcmp(key, "user") == 0 || strcmp(key, "group") == 0) {
+        if (parse_uint(v, &n) != 0)
+            return -1;
+        if (key[0] == 'u')
+            u->user = (uid_t)n;
+        else
+            u->group = (gid_t)n;
+        return 0;
+    }
+    return 0;
+}
+
+int unit_parse(struct unit *u, const char *name, const char *text,
+               char *err, size_t errlen)
+{
+    char line[512], section[32] = "", key[32];
+    int lineno = 0;
+
+    if (!unit_name_valid(name)) {
+        snprintf(err, errlen, "bad unit name");
+        return -1;
+    }
+
+    /* Defaults */
+    memset(u, 0, offsetof(struct unit, state));
+    strcpy(u->name, name);
+    u->restart_delay_ms = 500;
+    u->restart_max_delay_ms = 30000;
+    u->ready_timeout_ms = 10000;
+    u->stop_timeout_ms = 10000;
+    u->enabled = 1;
+    u->check_interval_ms = 5000;
+    u->check_timeout_ms = 2000;
+    u->filter_mode = -1;
+    u->filter_action = FILTER_KILL;
+
+    while (*text) {
+        size_t len = strcspn(text, "\n");
+        lineno++;
+        if (len >= sizeof(line)) {
+            snprintf(err, errlen, "line %d: too long", lineno);
+            return -1;
+        }
+        memcpy(line, text, len);
+        line[len] = '\0';
+        text += len + (text[len] == '\n');
+
+        const char *p = skip_ws(line);
+        size_t end = strlen(p);
+        while (end && (p[end - 1] == ' ' || p[end - 1] == '\t' || p[end - 1] == '\r'))
+            end--;
+        ((char *)p)[end] = '\0';
+        if (*p == '\0' || *p == '#')
+            continue;
+
+        if (*p == '[') {
+            const char *close = strchr(p, ']');
+            size_t n = close ? (size_t)(close - p - 1) : 0;
+            if (!close || n == 0 || n >= sizeof(section) || !at_end(close + 1)) {
+                snprintf(err, errlen, "line %d: bad section header", lineno);
+                return -1;
+            }
+            memcpy(section, p + 1, n);
+            section[n] = '\0';
+            continue;
+        }
+
+        const char *eq = strchr(p, '=');
+        size_t klen = eq ? (size_t)(eq - p) : 0;
+        while (klen && (p[klen - 1] == ' ' || p[klen - 1] == '\t'))
+            klen--;
+        if (!eq || klen == 0 || klen >= sizeof(key)) {
+            snprintf(err, errlen, "line %d: expected key = value", lineno);
+            return -1;
+        }
+        memcpy(key, p, klen);
+        key[klen] = '\0';
+        if (set_key(u, section, key, skip_ws(eq + 1)) != 0) {
+            snprintf(err, errlen, "line %d: bad value for %s", lineno, key);
+            return -1;
+        }
+    }
+
+    if (u->exec[0] == '\0') {
+        snprintf(err, errlen, "missing [service] exec");
+        return -1;
+    }
+    if (u->check_endpoint[0] == '\0')
+        strcpy(u->check_endpoint, u->name);
+    return 0;
+}
+
+struct unit *unit_find(struct unit *units, int count, const char *name)
+{
+    for (int i = 0; i < count; i++)
+        if (strcmp(units[i].name, name) == 0)
+            return &units[i];
+    return NULL;
+}
+
+static int read_text(const char *path, char *buf, size_t cap)
+{
+    int fd = open(path, O_RDONLY);
+    size_t used = 0;
+    ssize_t n;
+
+    if (fd < 0)
+        return -1;
+    while (used + 1 < cap && (n = read(fd, buf + used, cap - 1 - used)) > 0)
+        used += (size_t)n;
+    close(fd);
+    if (used + 1 >= cap)
+        return -1;
+    buf[used] = '\0';
+    return 0;
+}
+
+int unit_load_dir(const char *dir, struct unit *units, int count)
+{
+    static char text[UNIT_FILE_MAX];
+    static struct unit parsed;
+    char path[UNIT_STR_MAX * 2], name[INIT_NAME_MAX + 1], err[96];
+    DIR *d = opendir(dir);
+    struct dirent *ent;
+
+    if (!d)
+        return count;
+    while ((ent = readdir(d)) != NULL) {
+        size_t len = strlen(ent->d_name);
+        if (len <= 5 || strcmp(ent->d_name + len - 5, ".toml") != 0 ||
+            len - 5 > INIT_NAME_MAX)
+            continue;
+        memcpy(name, ent->d_name, len - 5);
+        name[len - 5] = '\0';
+        snprintf(path, sizeof(path), "%s/%s.toml", dir, name);
+
+        if (read_text(path, text, sizeof(text)) != 0) {
+            printf("[init] %s: unreadable or too large\n", path);
+            continue;
+        }
+        if (unit_parse(&parsed, name, text, err, sizeof(err)) != 0) {
+            printf("[init] %s: %s\n", path, err);
+            continue;
+        }
+
+        struct unit *u = unit_find(units, count, name);
+        if (!u) {
+            if (count >= UNIT_MAX) {
+                printf("[init] %s: too many units\n", path);
+                continue;
+            }
+            u = &units[count++];
+            memset(u, 0, sizeof(*u));
+        }
+        /* Keep the runtime state of a unit that was already loaded */
+        memcpy(u, &parsed, offsetof(struct unit, state));
+    }
+    closedir(d);
+    return count;
+}
+
+/* Whether unit `start` can be reached from the dependencies of unit i. */
+static int reaches(struct unit *units, int count, int i, int start, char *seen)
+{
+    struct unit *u = &units[i];
+
+    for (int d = 0; d < u->nrequires + u->nafter; d++) {
+        const char *dep = d < u->nrequires ? u->requires[d] : u->after[d - u->nrequires];
+        struct unit *t = unit_find(units, count, dep);
+        if (!t)
+            continue;
+        int j = (int)(t - units);
+        if (j == start)
+            return 1;
+        if (!seen[j]) {
+            seen[j] = 1;
+            if (reaches(units, count, j, start, seen))
+                return 1;
+        }
+    }
+    return 0;
+}
+
+int unit_check_cycles(struct unit *units, int count)
+{
+    int found = 0;
+
+    for (int i = 0; i < count; i++) {
+        char seen[UNIT_MAX] = { 0 };
+        units[i].cyclic = reaches(units, count, i, i, seen);
+        if (units[i].cyclic) {
+            units[i].state = ST_FAILED;
+            snprintf(units[i].reason, sizeof(units[i].reason), "dependency cycle");
+            found++;
+        }
+    }
+    return found;
+}
+
+const char *unit_state_name(enum unit_state state)
+{
+    static const char *const names[] = {
+        "stopped", "waiting", "starting", "running", "backoff", "stopping", "failed",
+    };
+    return names[state];
+}
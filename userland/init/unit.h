@@ -0,0 +1,122 @@
+/*
+ * unit.h -- service unit definitions for init
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A unit is one service, defined by /etc/init/<name>.toml. The format is
+ * documented in kernel/src/services/unit_file.rs, which parses the same
+ * files for the kernel's `service` builtin.
+ */
+
+#ifndef INIT_UNIT_H
+#define INIT_UNIT_H
+
+#include <stdint.h>
+#include <sys/types.h>
+#include <veridian/init.h>
+
+#define UNIT_MAX            32
+#define UNIT_MAX_DEPS       8
+#define UNIT_MAX_ARGS       8
+#define UNIT_MAX_ENV        8
+#define UNIT_STR_MAX        128
+#define UNIT_MAX_FILTER     32
+
+enum unit_type { UNIT_SIMPLE, UNIT_NOTIFY };
+enum unit_restart { RESTART_NO, RESTART_ON_FAILURE, RESTART_ALWAYS };
+enum unit_check { CHECK_NONE, CHECK_HEARTBEAT, CHECK_PING };
+enum unit_critical { CRITICAL_NO, CRITICAL_REBOOT, CRITICAL_RESCUE };
+
+enum unit_state {
+    ST_STOPPED,     /* not running, not wanted */
+    ST_WAITING,     /* wanted, waiting for its dependencies */
+    ST_STARTING,    /* spawned, waiting for INIT_OP_READY */
+    ST_RUNNING,
+    ST_BACKOFF,     /* exited, restart scheduled */
+    ST_STOPPING,    /* SIGTERM sent */
+    ST_FAILED,
+};
+
+struct unit {
+    /* Definition */
+    char name[INIT_NAME_MAX + 1];
+    char description[UNIT_STR_MAX];
+    char exec[UNIT_STR_MAX];
+    char args[UNIT_MAX_ARGS][UNIT_STR_MAX];
+    int nargs;
+    char env[UNIT_MAX_ENV][UNIT_STR_MAX];
+    int nenv;
+    char dir[UNIT_STR_MAX];         /* working directory; empty = / */
+    char requires[UNIT_MAX_DEPS][INIT_NAME_MAX + 1];
+    int nrequires;
+    char after[UNIT_MAX_DEPS][INIT_NAME_MAX + 1];
+    int nafter;
+    enum unit_type type;
+    enum unit_restart restart;
+    unsigned restart_delay_ms;      /* first retry; doubles up to the max */
+    unsigned restart_max_delay_ms;
+    unsigned max_restarts;          /* consecutive; 0 = unlimited */
+    unsigned ready_timeout_ms;
+    unsigned stop_timeout_ms;
+    uid_t user;
+    gid_t group;
+    int console;                    /* own the console (login sessions) */
+    int enabled;                    /* start at boot */
+    enum unit_critical critical;    /* what to do once it fails for good */
+    enum unit_check check;          /* liveness check while running */
+    char check_endpoint[INIT_NAME_MAX + 1];    /* ping target */
+    unsigned check_interval_ms;
+    unsigned check_timeout_ms;
+    unsigned limit_memory_mb;       /* RLIMIT_AS; 0 = inherit init's */
+    unsigned limit_cpu_s;           /* RLIMIT_CPU */
+    unsigned limit_open_files;      /* RLIMIT_NOFILE */
+    unsigned limit_processes;       /* RLIMIT_NPROC */
+    int filter_mode;                /* FILTER_ALLOW or _DENY; -1 = none */
+    unsigned filter_action;         /* FILTER_KILL, _ERRNO or _LOG */
+    uint32_t filter[UNIT_MAX_FILTER];   /* syscalls and FILTER_GROUP_* */
+    int nfilter;
+
+    /* Runtime */
+    enum unit_state state;
+    pid_t pid;
+    unsigned restarts;              /* consecutive restarts so far */
+    long long deadline_ms;          /* ready, restart or kill deadline */
+    long long started_ms;
+    char reason[64];                /* why it stopped or failed */
+    int restart_pending;            /* start again once stopped */
+    const char *kill_reason;        /* why init killed it, if it did */
+    int healthy;
+    unsigned check_failures;        /* liveness failures so far */
+    long long last_ok_ms;           /* start, last heartbeat or pong */
+    long long ping_sent_ms;         /* outstanding ping, or 0 */
+    int cyclic;                     /* part of a dependency cycle */
+};
+
+/*
+ * Parse unit file `text` for unit `name` into `u`'s definition, keeping its
+ * runtime state. Returns 0, or -1 with a message in `err`.
+ */
+int unit_parse(struct unit *u, const char *name, const char *text,
+               char *err, size_t errlen);
+
+/* Whether `name` is a valid unit name. */
+int unit_name_valid(const char *name);
+
+/*
+ * Load every unit in `dir` into `units` (at most UNIT_MAX), replacing the
+ * definitions of units already present. Returns the new count.
+ */
+int unit_load_dir(const char *dir, struct unit *units, int count);
+
+/*
+ * Flag the units that are part of a dependency cycle (through requires or
+ * after) and mark them failed. Returns the number of such units.
+ */
+int unit_check_cycles(struct unit *units, int count);
+
+struct unit *unit_find(struct unit *units, int count, const char *name);
+
+const char *unit_state_name(enum unit_state state);
+
+#endif /* INIT_UNIT_H */
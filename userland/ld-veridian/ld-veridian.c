@@ -9,11 +9,16 @@
  *   4. Loads LD_PRELOAD libraries (symbols override all later objects)
  *   5. Processes DT_NEEDED shared libraries (recursive dlopen)
  *   6. Performs relocations on all loaded objects (RELA, JMPREL)
- *   7. Sets up Thread-Local Storage (TLS) via ARCH_SET_FS
+ *   7. Sets up Thread-Local Storage (TLS) via ARCH_SET_FS (TPIDR_EL0 / tp
+ *      on AArch64 / RISC-V)
  *   8. Applies PT_GNU_RELRO protection
  *   9. Calls DT_INIT / DT_INIT_ARRAY constructors
  *  10. Transfers control to the application's entry point (AT_ENTRY)
  *
+ * Syscalls, the entry point and the thread pointer are implemented for
+ * x86_64, AArch64 and RISC-V; relocation processing and the TLS block
+ * layout (variant II) are x86_64-only so far.
+ *
  * Supported relocation types (x86_64):
  *   R_X86_64_NONE      (0)  -- ignored
  *   R_X86_64_64        (1)  -- S + A (symbol + addend)
@@ -28,7 +33,7 @@
  *
  * Library search: LD_LIBRARY_PATH, then DT_RUNPATH, then /lib, /usr/lib
  *
- * Build: x86_64-veridian-gcc -nostdlib -shared -fPIC -o ld-veridian.so ld-veridian.c
+ * Build: <arch>-veridian-gcc -nostdlib -shared -fPIC -o ld-veridian.so ld-veridian.c
  *
  * IMPORTANT: This file uses ONLY raw syscalls (no libc). It runs before any
  * libc initialization. All helper functions are self-contained.
@@ -48,7 +53,7 @@
 #define SYS_FILE_READ      52   /* read(fd, buf, len) */
 #define SYS_FILE_WRITE     53   /* write(fd, buf, len) */
 #define SYS_FILE_SEEK      54   /* lseek(fd, off, whence) */
-#define SYS_ARCH_PRCTL     158  /* arch_prctl(code, addr) */
+#define SYS_ARCH_PRCTL     203  /* arch_prctl(code, addr) */
 
 /* mmap / mprotect constants */
 #define PROT_NONE   0x0
@@ -286,6 +291,14 @@ typedef struct {
 
 /* ===== Raw Syscall Wrappers ===== */
 
+/*
+ *   x86_64:  syscall -- nr in rax, args in rdi/rsi/rdx/r10/r8/r9
+ *   aarch64: svc #0  -- nr in x8,  args in x0-x5
+ *   riscv64: ecall   -- nr in a7,  args in a0-a5
+ */
+
+#if defined(__x86_64__)
+
 static inline long _syscall0(long n)
 {
     long ret;
@@ -354,6 +367,170 @@ static inline long _syscall6(long n, long a1, long a2, long a3,
     return ret;
 }
 
+#elif defined(__aarch64__)
+
+static inline long _syscall0(long n)
+{
+    register long x8 __asm__("x8") = n;
+    register long x0 __asm__("x0");
+    __asm__ volatile("svc #0"
+        : "=r"(x0)
+        : "r"(x8)
+        : "memory");
+    return x0;
+}
+
+static inline long _syscall1(long n, long a1)
+{
+    register long x8 __asm__("x8") = n;
+    register long x0 __asm__("x0") = a1;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8)
+        : "memory");
+    return x0;
+}
+
+static inline long _syscall2(long n, long a1, long a2)
+{
+    register long x8 __asm__("x8") = n;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1)
+        : "memory");
+    return x0;
+}
+
+static inline long _syscall3(long n, long a1, long a2, long a3)
+{
+    register long x8 __asm__("x8") = n;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2)
+        : "memory");
+    return x0;
+}
+
+static inline long _syscall4(long n, long a1, long a2, long a3, long a4)
+{
+    register long x8 __asm__("x8") = n;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    register long x3 __asm__("x3") = a4;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2), "r"(x3)
+        : "memory");
+    return x0;
+}
+
+static inline long _syscall6(long n, long a1, long a2, long a3,
+                              long a4, long a5, long a6)
+{
+    register long x8 __asm__("x8") = n;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    register long x3 __asm__("x3") = a4;
+    register long x4 __asm__("x4") = a5;
+    register long x5 __asm__("x5") = a6;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2), "r"(x3), "r"(x4), "r"(x5)
+        : "memory");
+    return x0;
+}
+
+#elif defined(__riscv) && __riscv_xlen == 64
+
+static inline long _syscall0(long n)
+{
+    register long a7 __asm__("a7") = n;
+    register long a0 __asm__("a0");
+    __asm__ volatile("ecall"
+        : "=r"(a0)
+        : "r"(a7)
+        : "memory");
+    return a0;
+}
+
+static inline long _syscall1(long n, long a1)
+{
+    register long a7 __asm__("a7") = n;
+    register long a0 __asm__("a0") = a1;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7)
+        : "memory");
+    return a0;
+}
+
+static inline long _syscall2(long n, long a1, long a2)
+{
+    register long a7 __asm__("a7") = n;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1)
+        : "memory");
+    return a0;
+}
+
+static inline long _syscall3(long n, long a1, long a2, long a3)
+{
+    register long a7 __asm__("a7") = n;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2)
+        : "memory");
+    return a0;
+}
+
+static inline long _syscall4(long n, long a1, long a2, long a3, long a4)
+{
+    register long a7 __asm__("a7") = n;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    register long ra3 __asm__("a3") = a4;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2), "r"(ra3)
+        : "memory");
+    return a0;
+}
+
+static inline long _syscall6(long n, long a1, long a2, long a3,
+                              long a4, long a5, long a6)
+{
+    register long a7 __asm__("a7") = n;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    register long ra3 __asm__("a3") = a4;
+    register long ra4 __asm__("a4") = a5;
+    register long ra5 __asm__("a5") = a6;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2), "r"(ra3), "r"(ra4), "r"(ra5)
+        : "memory");
+    return a0;
+}
+
+#else
+#error "Unsupported architecture for ld-veridian"
+#endif
+
 /* ===== Basic String/Memory Helpers (no libc) ===== */
 
 static size_t _strlen(const char *s)
@@ -526,10 +703,12 @@ static long _mprotect(void *addr, size_t len, int prot)
     return _syscall3(SYS_MEMORY_PROTECT, (long)addr, (long)len, (long)prot);
 }
 
+#if defined(__x86_64__)
 static long _arch_prctl(int code, unsigned long addr)
 {
     return _syscall2(SYS_ARCH_PRCTL, (long)code, (long)addr);
 }
+#endif
 
 static void _exit(int code) __attribute__((noreturn));
 static void _exit(int code)
@@ -1323,6 +1502,7 @@ static void setup_tls_for_object(LoadedLib *lib)
     /* Self-pointer at TP (required for %fs:0 access pattern) */
     *(uint64_t *)tp = tp;
 
+#if defined(__x86_64__)
     /* Set FS base via arch_prctl */
     long ret = _arch_prctl(ARCH_SET_FS, tp);
     if (ret < 0) {
@@ -1332,6 +1512,14 @@ static void setup_tls_for_object(LoadedLib *lib)
     } else {
         dl_debug_addr("  TLS: FS base set to ", tp);
     }
+#elif defined(__aarch64__)
+    /* The thread pointer register is writable from EL0 */
+    __asm__ volatile("msr tpidr_el0, %0" : : "r"(tp));
+    dl_debug_addr("  TLS: TPIDR_EL0 set to ", tp);
+#else
+    __asm__ volatile("mv tp, %0" : : "r"(tp));
+    dl_debug_addr("  TLS: tp set to ", tp);
+#endif
 }
 
 /* ===== Init/Fini Functions ===== */
@@ -1836,6 +2024,7 @@ void _linker_main(long *sp)
 
 /* ===== _start: Assembly Entry Point ===== */
 
+#if defined(__x86_64__)
 __asm__(
     ".global _start\n"
     "_start:\n"
@@ -1845,3 +2034,27 @@ __asm__(
     "    call _linker_main\n"    /* Call C entry */
     "    ud2\n"                   /* Should never return */
 );
+#elif defined(__aarch64__)
+__asm__(
+    ".global _start\n"
+    "_start:\n"
+    "    mov x29, #0\n"          /* Clear frame pointer (ABI) */
+    "    mov x30, #0\n"          /* Clear link register */
+    "    mov x0, sp\n"           /* Pass stack pointer as arg1 */
+    "    and x1, x0, #-16\n"     /* Align stack to 16 bytes */
+    "    mov sp, x1\n"
+    "    bl _linker_main\n"      /* Call C entry */
+    "    brk #0\n"               /* Should never return */
+);
+#else
+__asm__(
+    ".global _start\n"
+    "_start:\n"
+    "    li fp, 0\n"             /* Clear frame pointer (ABI) */
+    "    li ra, 0\n"             /* Clear return address */
+    "    mv a0, sp\n"            /* Pass stack pointer as arg1 */
+    "    andi sp, sp, -16\n"     /* Align stack to 16 bytes */
+    "    call _linker_main\n"    /* Call C entry */
+    "    ebreak\n"               /* Should never return */
+);
+#endif
@@ -25,6 +25,12 @@ struct group *getgrnam(const char *name);
 struct group *getgrgid(gid_t gid);
 int getgrouplist(const char *user, gid_t group, gid_t *groups, int *ngroups);
 
+/** Set the supplementary group list (requires privilege). */
+int setgroups(size_t size, const gid_t *list);
+
+/** Set the supplementary groups to `group` plus those listing `user`. */
+int initgroups(const char *user, gid_t group);
+
 /** Rewind the group database to the beginning. */
 void setgrent(void);
 
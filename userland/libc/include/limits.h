@@ -49,6 +49,7 @@
 #define OPEN_MAX    256
 #define ARG_MAX     131072
 #define LINE_MAX    2048
+#define NGROUPS_MAX 32      /* Supplementary groups per process (kernel limit) */
 
 /* POSIX ssize_t limit */
 #define SSIZE_MAX   __LONG_MAX__
@@ -31,6 +31,16 @@ extern "C" {
 #define IFF_PROMISC     0x100
 #define IFF_MULTICAST   0x1000
 
+/* Device map (kept so struct ifreq matches the 40-byte Linux layout) */
+struct ifmap {
+    unsigned long   mem_start;
+    unsigned long   mem_end;
+    unsigned short  base_addr;
+    unsigned char   irq;
+    unsigned char   dma;
+    unsigned char   port;
+};
+
 struct ifreq {
     char ifr_name[IFNAMSIZ];
     union {
@@ -43,6 +53,7 @@ struct ifreq {
         int             ifr_ifindex;
         int             ifr_metric;
         int             ifr_mtu;
+        struct ifmap    ifr_map;
         char            ifr_slave[IFNAMSIZ];
         char            ifr_newname[IFNAMSIZ];
         void           *ifr_data;
@@ -0,0 +1,20 @@
+/*
+ * VeridianOS libc -- <netinet/tcp.h>
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * TCP socket options (level IPPROTO_TCP).  Only TCP_NODELAY is acted on
+ * by the kernel; the others are accepted and ignored.
+ */
+
+#ifndef _NETINET_TCP_H
+#define _NETINET_TCP_H
+
+#define TCP_NODELAY     1
+#define TCP_MAXSEG      2
+#define TCP_KEEPIDLE    4
+#define TCP_KEEPINTVL   5
+#define TCP_KEEPCNT     6
+
+#endif /* _NETINET_TCP_H */
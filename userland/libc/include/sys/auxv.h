@@ -0,0 +1,44 @@
+/*
+ * VeridianOS libc -- <sys/auxv.h>
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Access to the auxiliary vector the kernel places after envp.
+ */
+
+#ifndef _SYS_AUXV_H
+#define _SYS_AUXV_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define AT_NULL         0   /* End of vector */
+#define AT_PHDR         3   /* Program headers of the executable */
+#define AT_PHENT        4   /* Size of one program header */
+#define AT_PHNUM        5   /* Number of program headers */
+#define AT_PAGESZ       6   /* System page size */
+#define AT_BASE         7   /* Interpreter load address */
+#define AT_ENTRY        9   /* Entry point of the executable */
+#define AT_UID          11
+#define AT_EUID         12
+#define AT_GID          13
+#define AT_EGID         14
+#define AT_HWCAP        16  /* Hardware capabilities */
+#define AT_CLKTCK       17  /* Clock ticks per second */
+#define AT_SECURE       23  /* Secure mode (setuid) */
+#define AT_RANDOM       25  /* Address of 16 random bytes */
+#define AT_HWCAP2       26  /* Extended hardware capabilities */
+#define AT_EXECFN       31  /* Filename of program */
+
+/* VeridianOS: base of the vDSO (not an ELF image, see <veridian/vdso.h>) */
+#define AT_VDSO         0x1000
+
+unsigned long getauxval(unsigned long type);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* _SYS_AUXV_H */
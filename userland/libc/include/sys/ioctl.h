@@ -34,11 +34,44 @@ extern "C" {
 #define TIOCGPGRP   0x540F
 /** Set process group ID of foreground process. */
 #define TIOCSPGRP   0x5410
+/** Make this terminal the controlling terminal of the caller's session. */
+#define TIOCSCTTY   0x540E
+/** Give up the controlling terminal. */
+#define TIOCNOTTY   0x5422
 /** Non-blocking I/O. */
 #define FIONBIO     0x5421
 /** Get number of bytes available for reading. */
 #define FIONREAD    0x541B
 
+/* ========================================================================= */
+/* Socket interface ioctl requests (struct ifreq / struct ifconf)            */
+/* ========================================================================= */
+
+/** Get interface name from ifr_ifindex. */
+#define SIOCGIFNAME     0x8910
+/** List interface addresses (struct ifconf). */
+#define SIOCGIFCONF     0x8912
+/** Get interface flags (IFF_*). */
+#define SIOCGIFFLAGS    0x8913
+/** Set interface flags; only IFF_UP is writable. */
+#define SIOCSIFFLAGS    0x8914
+/** Get interface IPv4 address. */
+#define SIOCGIFADDR     0x8915
+/** Set interface IPv4 address. */
+#define SIOCSIFADDR     0x8916
+/** Get interface IPv4 netmask. */
+#define SIOCGIFNETMASK  0x891B
+/** Set interface IPv4 netmask. */
+#define SIOCSIFNETMASK  0x891C
+/** Get interface MTU. */
+#define SIOCGIFMTU      0x8921
+/** Set interface MTU. */
+#define SIOCSIFMTU      0x8922
+/** Get hardware address (sa_family 1 = Ethernet, 772 = loopback). */
+#define SIOCGIFHWADDR   0x8927
+/** Get interface index from ifr_name. */
+#define SIOCGIFINDEX    0x8933
+
 /** Terminal window size structure. */
 struct winsize {
     unsigned short ws_row;      /* Rows, in characters */
@@ -0,0 +1,60 @@
+/*
+ * VeridianOS C Library -- <sys/random.h>
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Access to the kernel CSPRNG (the same generator behind /dev/urandom).
+ * The kernel seeds it from RDSEED/RDRAND, virtio-rng and timer jitter
+ * before init runs, so getrandom() never blocks.
+ */
+
+#ifndef _SYS_RANDOM_H
+#define _SYS_RANDOM_H
+
+#include <stddef.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* ========================================================================= */
+/* getrandom flags                                                           */
+/* ========================================================================= */
+
+#define GRND_NONBLOCK   0x0001  /* Do not block (never needed; accepted) */
+#define GRND_RANDOM     0x0002  /* Historic /dev/random pool (same source) */
+#define GRND_INSECURE   0x0004  /* Allow unseeded output (same source) */
+
+/* ========================================================================= */
+/* Function declarations                                                     */
+/* ========================================================================= */
+
+/**
+ * Fill a buffer with random bytes from the kernel CSPRNG.
+ *
+ * At most 256 bytes are returned per call; callers needing more must
+ * loop, as on other systems.
+ *
+ * @param buf     Buffer to fill.
+ * @param buflen  Number of bytes requested.
+ * @param flags   GRND_* flags or 0.
+ * @return Number of bytes written, or -1 on error (errno set).
+ */
+ssize_t getrandom(void *buf, size_t buflen, unsigned int flags);
+
+/**
+ * Fill a buffer with exactly @p length random bytes.
+ *
+ * @param buf     Buffer to fill.
+ * @param length  Number of bytes; must not exceed 256.
+ * @return 0 on success, -1 on error (EIO if length > 256).
+ */
+int getentropy(void *buf, size_t length);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* _SYS_RANDOM_H */
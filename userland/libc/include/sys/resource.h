@@ -4,8 +4,12 @@
  * Copyright (c) 2025-2026 VeridianOS Contributors
  * SPDX-License-Identifier: MIT OR Apache-2.0
  *
- * Resource usage and limits.  Stub implementations — getrlimit returns
- * RLIM_INFINITY for all resources, setrlimit is a no-op.
+ * Resource usage and limits.  Limits are per process and inherited across
+ * fork; the kernel enforces RLIMIT_AS, RLIMIT_DATA, RLIMIT_NOFILE,
+ * RLIMIT_NPROC and RLIMIT_CPU.  getrusage reports kernel-accounted user and
+ * system CPU time.  Priorities are nice values from -20 (highest) to 19;
+ * the kernel schedules them in a handful of classes, so getpriority may
+ * return a different value than was set.
  */
 
 #ifndef _SYS_RESOURCE_H
@@ -45,12 +49,24 @@ struct rlimit {
 #define RLIMIT_MEMLOCK  8   /* Max locked-in-memory address space */
 #define RLIMIT_AS       9   /* Max address space size */
 
+/* ========================================================================= */
+/* Priorities                                                                */
+/* ========================================================================= */
+
+#define PRIO_PROCESS    0   /* who is a process ID (only kind supported) */
+#define PRIO_PGRP       1
+#define PRIO_USER       2
+
+#define PRIO_MIN        (-20)
+#define PRIO_MAX        20
+
 /* ========================================================================= */
 /* Resource usage                                                            */
 /* ========================================================================= */
 
 #define RUSAGE_SELF     0
 #define RUSAGE_CHILDREN (-1)
+#define RUSAGE_THREAD   1
 
 struct rusage {
     struct timeval ru_utime;    /* User CPU time used */
@@ -84,6 +100,14 @@ int setrlimit(int resource, const struct rlimit *rlp);
 /** Get resource usage. */
 int getrusage(int who, struct rusage *usage);
 
+/** Get the nice value of a process (who 0 = caller).  Returns -1 with
+ *  errno set on error; clear errno first to tell it from a nice of -1. */
+int getpriority(int which, id_t who);
+
+/** Set the nice value of a process (who 0 = caller).  Other processes
+ *  need root or a MODIFY capability for them. */
+int setpriority(int which, id_t who, int prio);
+
 #ifdef __cplusplus
 }
 #endif
@@ -0,0 +1,35 @@
+/*
+ * VeridianOS libc -- <sys/sendfile.h>
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Copy data between descriptors without passing it through user space.
+ */
+
+#ifndef _SYS_SENDFILE_H
+#define _SYS_SENDFILE_H
+
+#include <veridian/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * Copy up to count bytes from in_fd to out_fd inside the kernel.
+ *
+ * @param out_fd    Destination: a socket, pipe or file.
+ * @param in_fd     Source file.
+ * @param offset    Read from *offset and advance it, leaving the file
+ *                  position of in_fd unchanged; NULL to use the position.
+ * @param count     Maximum bytes to copy.
+ * @return Bytes copied on success, -1 on error.
+ */
+ssize_t sendfile(int out_fd, int in_fd, off_t *offset, size_t count);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* _SYS_SENDFILE_H */
@@ -58,6 +58,7 @@ extern "C" {
 #define SO_SNDTIMEO     21
 #define SO_BINDTODEVICE 25
 #define SO_REUSEPORT    15
+#define SO_PEERCRED     17
 
 /* Shutdown modes */
 #define SHUT_RD         0
@@ -66,6 +67,7 @@ extern "C" {
 
 /* Message flags */
 #define MSG_PEEK        0x02
+#define MSG_CTRUNC      0x08
 #define MSG_WAITALL     0x100
 #define MSG_DONTWAIT    0x40
 #define MSG_NOSIGNAL    0x4000
@@ -90,24 +92,32 @@ struct iovec {
     size_t  iov_len;
 };
 
+/* Linux (glibc) layout; the kernel reads it as is */
 struct msghdr {
     void         *msg_name;
     socklen_t     msg_namelen;
     struct iovec *msg_iov;
-    int           msg_iovlen;
+    size_t        msg_iovlen;
     void         *msg_control;
-    socklen_t     msg_controllen;
+    size_t        msg_controllen;
     int           msg_flags;
 };
 
 /* Control message header for ancillary data (used by sendmsg/recvmsg) */
 struct cmsghdr {
-    socklen_t cmsg_len;     /* Data byte count including header */
+    size_t    cmsg_len;     /* Data byte count including header */
     int       cmsg_level;   /* Originating protocol (SOL_SOCKET) */
     int       cmsg_type;    /* Protocol-specific type (SCM_RIGHTS) */
     /* followed by unsigned char cmsg_data[] */
 };
 
+/* Peer credentials of a connected AF_UNIX socket (SO_PEERCRED) */
+struct ucred {
+    pid_t pid;
+    uid_t uid;
+    gid_t gid;
+};
+
 /* Ancillary data types */
 #define SCM_RIGHTS  1   /* Transfer file descriptors */
 #define SCM_CREDENTIALS 2
@@ -140,6 +150,7 @@ int socket(int domain, int type, int protocol);
 int bind(int sockfd, const struct sockaddr *addr, socklen_t addrlen);
 int listen(int sockfd, int backlog);
 int accept(int sockfd, struct sockaddr *addr, socklen_t *addrlen);
+int accept4(int sockfd, struct sockaddr *addr, socklen_t *addrlen, int flags);
 int connect(int sockfd, const struct sockaddr *addr, socklen_t addrlen);
 ssize_t send(int sockfd, const void *buf, size_t len, int flags);
 ssize_t recv(int sockfd, void *buf, size_t len, int flags);
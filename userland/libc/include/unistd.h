@@ -46,6 +46,11 @@ extern "C" {
 #define SEEK_END        2
 #endif
 
+#ifndef SEEK_DATA
+#define SEEK_DATA       3   /* Next offset holding data */
+#define SEEK_HOLE       4   /* Next hole (end of file counts) */
+#endif
+
 /* ========================================================================= */
 /* File I/O                                                                  */
 /* ========================================================================= */
@@ -74,9 +79,15 @@ int dup(int oldfd);
 /** Duplicate a file descriptor to a specific number. */
 int dup2(int oldfd, int newfd);
 
+/** Like dup2(), but fails if oldfd == newfd; flags may hold O_CLOEXEC. */
+int dup3(int oldfd, int newfd, int flags);
+
 /** Create a pipe. */
 int pipe(int pipefd[2]);
 
+/** Create a pipe; flags may hold O_CLOEXEC. */
+int pipe2(int pipefd[2], int flags);
+
 /** Delete a name from the filesystem. */
 int unlink(const char *pathname);
 
@@ -176,11 +187,24 @@ gid_t getgid(void);
 gid_t getegid(void);
 int setuid(uid_t uid);
 int setgid(gid_t gid);
+int seteuid(uid_t euid);
+int setegid(gid_t egid);
+int setreuid(uid_t ruid, uid_t euid);
+int setregid(gid_t rgid, gid_t egid);
+int getresuid(uid_t *ruid, uid_t *euid, uid_t *suid);
+int getresgid(gid_t *rgid, gid_t *egid, gid_t *sgid);
 int getgroups(int size, gid_t list[]);
 
 /** Get login name of the user. */
 char *getlogin(void);
 
+/**
+ * Hash a password (kernel PBKDF2-HMAC-SHA256).  `salt` is a setting of the
+ * form "$pbkdf2-sha256$<rounds>$<salt>" or a complete stored hash.  Returns
+ * a pointer to static storage, or NULL on error.
+ */
+char *crypt(const char *key, const char *salt);
+
 /** Get the hostname. */
 int gethostname(char *name, size_t len);
 
@@ -280,6 +304,9 @@ int fchown(int fd, uid_t owner, gid_t group);
 /** Change ownership of a symlink (no follow). */
 int lchown(const char *pathname, uid_t owner, gid_t group);
 
+/** Fill buf with length (<= 256) random bytes from the kernel CSPRNG. */
+int getentropy(void *buf, size_t length);
+
 /* ========================================================================= */
 /* getopt (POSIX requires these in unistd.h)                                 */
 /* ========================================================================= */
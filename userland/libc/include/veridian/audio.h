@@ -0,0 +1,83 @@
+/*
+ * VeridianOS PCM Playback API
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A stream plays interleaved signed 16-bit samples at 48000 Hz, mono or
+ * stereo; the kernel mixes all streams into the output device. Resample
+ * other rates before writing.
+ *
+ * The kernel buffers 4096 frames (about 85 ms) per stream. On top of
+ * that every veridian_pcm keeps its own ring buffer: veridian_pcm_write()
+ * queues into it and hands the kernel as much as it takes, so the caller
+ * never blocks. Call veridian_pcm_write() (with count 0) or
+ * veridian_pcm_drain() again to push the rest as the kernel buffer empties.
+ *
+ * Functions return 0 (or a frame count) on success and -1 with errno set
+ * on failure.
+ */
+
+#ifndef VERIDIAN_AUDIO_H
+#define VERIDIAN_AUDIO_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VERIDIAN_PCM_RATE       48000
+
+/* Default size of the user-side ring buffer, in frames. */
+#define VERIDIAN_PCM_RING_FRAMES 16384
+
+typedef struct veridian_pcm veridian_pcm;
+
+/* Output device defaults as reported by the kernel. */
+struct veridian_audio_info {
+    uint32_t sample_rate;
+    uint32_t channels;
+    uint32_t streams;   /* Open streams, all clients */
+};
+
+/* Query the output defaults. */
+int veridian_audio_info(struct veridian_audio_info *info);
+
+/*
+ * Open a playing stream with 1 or 2 channels and a ring of ring_frames
+ * frames (0: VERIDIAN_PCM_RING_FRAMES). Returns NULL with errno set.
+ */
+veridian_pcm *veridian_pcm_open(unsigned channels, size_t ring_frames);
+
+/*
+ * Queue up to `frames` frames and push what the kernel takes. Returns the
+ * number of frames queued, short when the ring buffer is full.
+ */
+long veridian_pcm_write(veridian_pcm *pcm, const int16_t *samples,
+                        size_t frames);
+
+/* Frames the ring buffer can still take. */
+size_t veridian_pcm_avail(const veridian_pcm *pcm);
+
+/* Push queued frames to the kernel, yielding until the ring is empty. */
+int veridian_pcm_drain(veridian_pcm *pcm);
+
+/* Set the stream volume, 0-100. */
+int veridian_pcm_set_volume(veridian_pcm *pcm, unsigned volume);
+
+int veridian_pcm_pause(veridian_pcm *pcm);
+int veridian_pcm_resume(veridian_pcm *pcm);
+
+/* Stop playback and drop everything queued, here and in the kernel. */
+int veridian_pcm_stop(veridian_pcm *pcm);
+
+/* Close the stream without draining it. */
+int veridian_pcm_close(veridian_pcm *pcm);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_AUDIO_H */
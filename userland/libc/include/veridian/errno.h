@@ -275,6 +275,9 @@ extern "C" {
 /** Resource limit exceeded (process table full, fd table full) */
 #define ERESOURCELIMIT      79  /* SyscallError::ResourceLimitExceeded = -79 */
 
+/** No data available (extended attribute not found) */
+#define ENODATA             80  /* SyscallError::NoData = -80 */
+
 /* ========================================================================= */
 /* POSIX-Compatible Aliases                                                  */
 /* ========================================================================= */
@@ -282,6 +285,7 @@ extern "C" {
 /** Same as EAGAIN (POSIX compatibility) */
 #define EWOULDBLOCK         EAGAIN
 #define EDEADLOCK           EDEADLK
+#define ENOATTR             ENODATA
 
 /* ========================================================================= */
 /* errno access                                                              */
@@ -171,6 +171,50 @@ int dup2(int oldfd, int newfd);
  */
 int pipe(int pipefd[2]);
 
+/* splice() flags.  All are hints and currently have no effect. */
+#define SPLICE_F_MOVE       0x01
+#define SPLICE_F_NONBLOCK   0x02
+#define SPLICE_F_MORE       0x04
+#define SPLICE_F_GIFT       0x08
+
+/**
+ * Move data between a pipe and another descriptor inside the kernel.
+ *
+ * @param fd_in     Source descriptor.
+ * @param off_in    Source offset (advanced), or NULL to use the file position.
+ * @param fd_out    Destination descriptor.
+ * @param off_out   Destination offset (advanced), or NULL.
+ * @param len       Maximum bytes to move.
+ * @param flags     SPLICE_F_* hints.
+ * @return Bytes moved on success, -1 on error.  One of fd_in and fd_out
+ *         must be a pipe, and its offset must be NULL.
+ */
+ssize_t splice(int fd_in, off_t *off_in, int fd_out, off_t *off_out,
+               size_t len, unsigned int flags);
+
+/* fallocate() mode flags */
+#define FALLOC_FL_KEEP_SIZE     0x01  /* Do not change the file size */
+#define FALLOC_FL_PUNCH_HOLE    0x02  /* Deallocate; needs KEEP_SIZE */
+
+/**
+ * Allocate or deallocate space for a byte range of a file.
+ *
+ * @param fd      File descriptor open for writing.
+ * @param mode    0 or FALLOC_FL_* flags.
+ * @param offset  Start of the range.
+ * @param len     Length of the range (> 0).
+ * @return 0 on success, -1 on error (EOPNOTSUPP if the filesystem does
+ *         not support it).
+ */
+int fallocate(int fd, int mode, off_t offset, off_t len);
+
+/**
+ * Ensure space is allocated for a byte range, extending the file if needed.
+ *
+ * @return 0 on success, or an error number (errno is not set).
+ */
+int posix_fallocate(int fd, off_t offset, off_t len);
+
 #ifdef __cplusplus
 }
 #endif
@@ -0,0 +1,80 @@
+/*
+ * VeridianOS Heap Profiling
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * With MALLOC_PROFILE set in its environment, a process's malloc keeps the
+ * counters below and registers them with SYS_HEAP_PROFILE, so that
+ * /proc/<pid>/heap (or `memprof <pid>` in the kernel shell) can show them
+ * while it runs:
+ *
+ *   MALLOC_PROFILE=counts      size-class histogram, live and peak bytes
+ *   MALLOC_PROFILE=sites       also the callers of every 32nd allocation
+ *   MALLOC_PROFILE=sites:N     ... of every Nth allocation
+ *
+ * A call site is the return address into the caller of malloc, calloc or
+ * realloc. Every allocation served by mmap is sampled. A site whose
+ * live_bytes keep growing is leaking.
+ */
+
+#ifndef VERIDIAN_HEAP_PROFILE_H
+#define VERIDIAN_HEAP_PROFILE_H
+
+#include <stdint.h>
+
+#include <veridian/syscall.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* veridian_heap_stats.mode */
+#define HEAP_PROFILE_OFF        0
+#define HEAP_PROFILE_COUNTS     1
+#define HEAP_PROFILE_SITES      2
+
+#define HEAP_PROFILE_MAGIC      0x56485046u     /* "VHPF" */
+#define HEAP_PROFILE_VERSION    1
+
+/* Class 0 holds sizes up to 16 bytes, class n up to 16 << n, the last one
+ * everything larger. */
+#define HEAP_PROFILE_CLASSES    24
+#define HEAP_PROFILE_SITES_MAX  64
+#define HEAP_PROFILE_INTERVAL   32
+
+struct veridian_heap_class {
+    uint64_t allocs;
+    uint64_t frees;
+    uint64_t bytes;
+};
+
+struct veridian_heap_site {
+    uint64_t pc;                /* return address into the caller; 0 = free */
+    uint64_t allocs;
+    uint64_t bytes;
+    uint64_t live_bytes;
+};
+
+struct veridian_heap_stats {
+    uint32_t magic;             /* HEAP_PROFILE_MAGIC */
+    uint32_t version;           /* HEAP_PROFILE_VERSION */
+    uint32_t mode;              /* HEAP_PROFILE_* */
+    uint32_t sample_interval;
+    uint64_t allocs;
+    uint64_t frees;
+    uint64_t live_bytes;
+    uint64_t peak_bytes;
+    uint64_t dropped;           /* samples with no free site slot */
+    struct veridian_heap_class classes[HEAP_PROFILE_CLASSES];
+    struct veridian_heap_site sites[HEAP_PROFILE_SITES_MAX];
+};
+
+/* The calling process's counters, or NULL when not profiling. */
+const struct veridian_heap_stats *veridian_heap_stats(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_HEAP_PROFILE_H */
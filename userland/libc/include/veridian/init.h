@@ -0,0 +1,118 @@
+/*
+ * VeridianOS init Control Protocol
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * PID 1 (userland/init) binds the "init" IPC endpoint. Services of
+ * type = "notify" send INIT_OP_READY once they can serve requests; `vctl`
+ * sends start/stop/restart/reload and power requests and receives the reply
+ * on the "init.reply.<pid>" endpoint it binds first. Unit state is
+ * published as a text table in INIT_STATUS_FILE.
+ *
+ * Power off, reboot and halt requests are answered as soon as init
+ * accepts them; init then stops every unit in reverse start order, syncs
+ * and calls SYS_REBOOT. Suspend leaves the units running and is answered
+ * after resume.
+ *
+ * Liveness checks: a unit with a "heartbeat" check sends INIT_OP_HEARTBEAT
+ * at least every interval; for a "ping" check init sends INIT_OP_PING to
+ * the service's own endpoint, and the service answers with
+ * INIT_OP_HEARTBEAT. Opcodes from 0x7000 up are reserved for init on every
+ * service endpoint.
+ *
+ * Every message is a small IPC message (struct init_msg, the layout of the
+ * kernel's SmallMessage in kernel/src/ipc/message.rs).
+ */
+
+#ifndef VERIDIAN_INIT_H
+#define VERIDIAN_INIT_H
+
+#include <stdint.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/syscall.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define INIT_SERVICE        "init"
+#define INIT_REPLY_PREFIX   "init.reply."
+
+/* Directory of unit files, one <name>.toml per service */
+#define INIT_UNIT_DIR       "/etc/init"
+/*
+ * Unit table, one TAB-separated line per unit: name, state, pid, restarts,
+ * health ("-", "ok" or "failed"), liveness failures, time of the last good
+ * check (CLOCK_MONOTONIC ms), critical action, reason, description
+ */
+#define INIT_STATUS_FILE    "/run/init/units"
+#define INIT_STATUS_FIELDS  10
+
+/* Longest unit name (matches kernel/src/services/unit_file.rs) */
+#define INIT_NAME_MAX       31
+
+/* Requests: data[0] = sender pid (READY, HEARTBEAT) or the unit name */
+#define INIT_OP_READY       1
+#define INIT_OP_START       2
+#define INIT_OP_STOP        3
+#define INIT_OP_RESTART     4
+#define INIT_OP_RELOAD      5
+#define INIT_OP_HEARTBEAT   6
+/* Power requests, no data */
+#define INIT_OP_POWEROFF    7
+#define INIT_OP_REBOOT      8
+#define INIT_OP_HALT        9
+#define INIT_OP_SUSPEND     10
+/* Reply: opcode = request opcode, data[0] = 0 or a negated errno */
+
+/* Sent by init to a service endpoint; answer with init_heartbeat() */
+#define INIT_OP_PING        0x7001
+
+struct init_msg {
+    uint64_t capability;
+    uint32_t opcode;
+    uint32_t flags;     /* requests: pid of the sender, for the reply */
+    uint64_t data[4];
+};
+
+/* Send init a message about the calling process. */
+static inline long init_send_self(uint32_t op)
+{
+    struct init_msg msg;
+    long cap = veridian_syscall1(SYS_IPC_LOOKUP_ENDPOINT, INIT_SERVICE);
+
+    if (cap < 0)
+        return cap;
+    memset(&msg, 0, sizeof(msg));
+    msg.capability = (uint64_t)cap;
+    msg.opcode = op;
+    msg.flags = (uint32_t)getpid();
+    msg.data[0] = (uint64_t)getpid();
+    return veridian_syscall4(SYS_IPC_SEND, cap, &msg, sizeof(msg), 0);
+}
+
+/*
+ * Tell init this service is ready. Call once from a type = "notify"
+ * service; harmless when not run by init. Returns 0 or a negative error.
+ */
+static inline long init_notify_ready(void)
+{
+    return init_send_self(INIT_OP_READY);
+}
+
+/*
+ * Tell init this service is alive: periodically for a "heartbeat" check,
+ * or on each INIT_OP_PING for a "ping" check. Returns 0 or a negative error.
+ */
+static inline long init_heartbeat(void)
+{
+    return init_send_self(INIT_OP_HEARTBEAT);
+}
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_INIT_H */
@@ -0,0 +1,91 @@
+/*
+ * VeridianOS Input Injection
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * SYS_INPUT_INJECT feeds synthetic keyboard and pointer events into the
+ * desktop's input stream, for on-screen keyboards and other accessibility
+ * tools. The caller needs the input-injection capability, which root hands
+ * out with input_grant_inject(); root itself may always inject.
+ *
+ * Key codes are the bytes the keyboard produces: ASCII, plus the
+ * INPUT_KEY_* codes for arrows and editing keys. Only presses are
+ * delivered, and held or sticky Shift/Ctrl are applied as for typed keys.
+ * INPUT_KEY_MOD_* press and release modifiers. A batch with any malformed
+ * event is rejected as a whole.
+ */
+
+#ifndef VERIDIAN_INPUT_H
+#define VERIDIAN_INPUT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include <veridian/syscall.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* SYS_INPUT_INJECT operations */
+#define INPUT_INJECT            0       /* events, count -> count */
+#define INPUT_GRANT_INJECT      1       /* pid (root only) */
+
+#define INPUT_MAX_INJECT        64
+
+/* input_event.type */
+#define INPUT_EV_KEY            0x01
+#define INPUT_EV_REL            0x02
+#define INPUT_EV_ABS            0x03
+
+/* input_event.code for INPUT_EV_REL / INPUT_EV_ABS */
+#define INPUT_AXIS_X            0x00
+#define INPUT_AXIS_Y            0x01
+
+/* input_event.code for INPUT_EV_KEY beyond ASCII */
+#define INPUT_KEY_UP            0x80
+#define INPUT_KEY_DOWN          0x81
+#define INPUT_KEY_LEFT          0x82
+#define INPUT_KEY_RIGHT         0x83
+#define INPUT_KEY_HOME          0x84
+#define INPUT_KEY_END           0x85
+#define INPUT_KEY_DELETE        0x86
+#define INPUT_KEY_MOD_SHIFT     0x90
+#define INPUT_KEY_MOD_CTRL      0x91
+#define INPUT_KEY_MOD_ALT       0x92
+#define INPUT_KEY_MOD_SUPER     0x93
+#define INPUT_BTN_LEFT          0x110
+#define INPUT_BTN_RIGHT         0x111
+#define INPUT_BTN_MIDDLE        0x112
+
+/* Same layout as the events read with SYS_INPUT_READ */
+struct input_event {
+    uint64_t timestamp;                 /* ignored on injection */
+    uint16_t type;
+    uint16_t code;
+    int32_t value;                      /* 1 press, 0 release; axis value */
+};
+
+static inline long input_inject(const struct input_event *events, size_t count)
+{
+    return veridian_syscall3(SYS_INPUT_INJECT, INPUT_INJECT, events, count);
+}
+
+static inline long input_grant_inject(long pid)
+{
+    return veridian_syscall2(SYS_INPUT_INJECT, INPUT_GRANT_INJECT, pid);
+}
+
+/* Press (and for modifiers, release) one key. */
+static inline long input_inject_key(uint16_t code, int pressed)
+{
+    struct input_event ev = { 0, INPUT_EV_KEY, code, pressed ? 1 : 0 };
+    return input_inject(&ev, 1);
+}
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_INPUT_H */
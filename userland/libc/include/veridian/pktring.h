@@ -0,0 +1,166 @@
+/*
+ * VeridianOS Kernel-Bypass Packet Rings
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A process holding a capability for a network interface attaches a ring
+ * set to it with SYS_NET_PACKET_RING and exchanges raw Ethernet frames
+ * with the driver through shared memory, bypassing the kernel's TCP/IP
+ * stack. Root hands out the capability with PR_GRANT.
+ *
+ * The ring set is mapped at pr_attach.addr:
+ *
+ *   0                  struct pr_header
+ *   PR_RX_DESC_OFFSET  RX descriptors, PR_MAX_SLOTS x struct pr_desc
+ *   PR_TX_DESC_OFFSET  TX descriptors, PR_MAX_SLOTS x struct pr_desc
+ *   PR_FRAMES_OFFSET   rx_slots RX frame slots, then tx_slots TX slots,
+ *                      PR_SLOT_SIZE bytes each
+ *
+ * Indices are free-running 32-bit counters; index i lives in slot
+ * i % slots. The kernel advances rx_producer and tx_consumer, the process
+ * advances rx_consumer and tx_producer. Queued TX frames are sent on
+ * PR_KICK, or eventually by the kernel's network poll loop.
+ *
+ * With PR_DIVERT, received frames matching the EtherType filter no longer
+ * reach the kernel stack; otherwise the ring sees a copy.
+ */
+
+#ifndef VERIDIAN_PKTRING_H
+#define VERIDIAN_PKTRING_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include <veridian/syscall.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* SYS_NET_PACKET_RING operations */
+#define PR_ATTACH               0       /* struct pr_attach * -> ring id */
+#define PR_DETACH               1       /* ring id */
+#define PR_KICK                 2       /* ring id -> frames sent */
+#define PR_WAIT                 3       /* ring id, timeout ms (0 = none) */
+#define PR_GRANT                4       /* pid, ifindex (root only) */
+
+/* pr_attach.flags */
+#define PR_DIVERT               0x1
+
+#define PR_MAGIC                0x56524E47u     /* "VRNG" */
+#define PR_SLOT_SIZE            2048
+#define PR_MAX_SLOTS            256
+#define PR_RX_DESC_OFFSET       64
+#define PR_TX_DESC_OFFSET       (PR_RX_DESC_OFFSET + PR_MAX_SLOTS * 4)
+#define PR_FRAMES_OFFSET        4096
+
+struct pr_attach {
+    uint32_t ifindex;
+    uint32_t rx_slots;          /* power of two, at most PR_MAX_SLOTS */
+    uint32_t tx_slots;          /* power of two, at most PR_MAX_SLOTS */
+    uint32_t flags;             /* PR_DIVERT */
+    uint16_t ethertype;         /* host order; 0 = every frame */
+    uint16_t _pad[3];
+    uint64_t addr;              /* out: where the ring set is mapped */
+    uint64_t size;              /* out: its size in bytes */
+};
+
+struct pr_header {
+    uint32_t magic;
+    uint32_t version;
+    uint32_t rx_slots;
+    uint32_t tx_slots;
+    uint32_t slot_size;
+    uint32_t flags;
+    volatile uint32_t rx_producer;
+    volatile uint32_t rx_consumer;
+    volatile uint32_t tx_producer;
+    volatile uint32_t tx_consumer;
+    volatile uint32_t rx_dropped;   /* frames lost to a full RX ring */
+    volatile uint32_t tx_errors;    /* TX descriptors the kernel rejected */
+    uint32_t _reserved[4];
+};
+
+struct pr_desc {
+    uint16_t len;
+    uint16_t flags;
+};
+
+static inline long pr_attach(struct pr_attach *req)
+{
+    return veridian_syscall3(SYS_NET_PACKET_RING, PR_ATTACH, req, 0);
+}
+
+static inline long pr_detach(long ring)
+{
+    return veridian_syscall3(SYS_NET_PACKET_RING, PR_DETACH, ring, 0);
+}
+
+static inline long pr_kick(long ring)
+{
+    return veridian_syscall3(SYS_NET_PACKET_RING, PR_KICK, ring, 0);
+}
+
+static inline long pr_wait(long ring, unsigned long timeout_ms)
+{
+    return veridian_syscall3(SYS_NET_PACKET_RING, PR_WAIT, ring, timeout_ms);
+}
+
+static inline struct pr_desc *pr_desc(void *base, size_t offset, uint32_t slot)
+{
+    return (struct pr_desc *)((char *)base + offset) + slot;
+}
+
+/*
+ * Next received frame, or NULL if none; *len receives its length. Release
+ * it with pr_rx_release() once done.
+ */
+static inline void *pr_rx_peek(void *base, uint16_t *len)
+{
+    struct pr_header *h = (struct pr_header *)base;
+    uint32_t cons = h->rx_consumer;
+    uint32_t slot;
+
+    if (cons == __atomic_load_n(&h->rx_producer, __ATOMIC_ACQUIRE))
+        return NULL;
+    slot = cons % h->rx_slots;
+    *len = pr_desc(base, PR_RX_DESC_OFFSET, slot)->len;
+    return (char *)base + PR_FRAMES_OFFSET + (size_t)slot * PR_SLOT_SIZE;
+}
+
+static inline void pr_rx_release(void *base)
+{
+    struct pr_header *h = (struct pr_header *)base;
+
+    __atomic_store_n(&h->rx_consumer, h->rx_consumer + 1, __ATOMIC_RELEASE);
+}
+
+/* Free TX slot to build a frame in, or NULL if the ring is full. */
+static inline void *pr_tx_slot(void *base)
+{
+    struct pr_header *h = (struct pr_header *)base;
+    uint32_t prod = h->tx_producer;
+
+    if (prod - __atomic_load_n(&h->tx_consumer, __ATOMIC_ACQUIRE) >= h->tx_slots)
+        return NULL;
+    return (char *)base + PR_FRAMES_OFFSET +
+           (size_t)(h->rx_slots + prod % h->tx_slots) * PR_SLOT_SIZE;
+}
+
+/* Queue the frame built in pr_tx_slot() for transmission. */
+static inline void pr_tx_commit(void *base, uint16_t len)
+{
+    struct pr_header *h = (struct pr_header *)base;
+    uint32_t prod = h->tx_producer;
+
+    pr_desc(base, PR_TX_DESC_OFFSET, prod % h->tx_slots)->len = len;
+    pr_desc(base, PR_TX_DESC_OFFSET, prod % h->tx_slots)->flags = 0;
+    __atomic_store_n(&h->tx_producer, prod + 1, __ATOMIC_RELEASE);
+}
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_PKTRING_H */
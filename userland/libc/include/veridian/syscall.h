@@ -4,7 +4,8 @@
  * Copyright (c) 2025-2026 VeridianOS Contributors
  * SPDX-License-Identifier: MIT OR Apache-2.0
  *
- * Syscall numbers and inline assembly wrappers matching kernel/src/syscall/mod.rs.
+ * Syscall numbers and inline assembly wrappers matching libs/veridian-abi,
+ * the syscall table the kernel is built from (its tests check this file).
  * Architecture-specific calling conventions:
  *   x86_64:  syscall instruction, nr in rax, args in rdi/rsi/rdx/r10/r8/r9
  *   aarch64: svc #0, nr in x8, args in x0-x5
@@ -24,7 +25,7 @@ extern "C" {
 /* Syscall Numbers                                                           */
 /* ========================================================================= */
 
-/* IPC system calls (0-7) */
+/* IPC system calls (0-8) */
 #define SYS_IPC_SEND            0
 #define SYS_IPC_RECEIVE         1
 #define SYS_IPC_CALL            2
@@ -33,6 +34,7 @@ extern "C" {
 #define SYS_IPC_BIND_ENDPOINT   5
 #define SYS_IPC_SHARE_MEMORY   6
 #define SYS_IPC_MAP_MEMORY      7
+#define SYS_IPC_LOOKUP_ENDPOINT 8
 
 /* Process management (10-18) */
 #define SYS_PROCESS_YIELD       10
@@ -51,9 +53,11 @@ extern "C" {
 #define SYS_MEMORY_PROTECT      22
 #define SYS_MEMORY_BRK          23
 
-/* Capability management (30-31) */
+/* Capability management (30-33) */
 #define SYS_CAPABILITY_GRANT    30
 #define SYS_CAPABILITY_REVOKE   31
+#define SYS_CAP_INSPECT         32
+#define SYS_AUDIT_READ          33
 
 /* Thread management (40-46) */
 #define SYS_THREAD_CREATE       40
@@ -91,15 +95,85 @@ extern "C" {
 #define SYS_FS_SYNC             72
 #define SYS_FS_FSYNC            73
 
-/* Kernel information (80) */
+/* Kernel information and control (80-86) */
 #define SYS_KERNEL_GET_INFO     80
-
-/* Package management (90-94) */
+#define SYS_DMESG               81
+#define SYS_TRACE_CTL           82
+#define SYS_CRASH_DUMP          83
+#define SYS_WATCHDOG            84
+#define SYS_REBOOT              85
+#define SYS_ABI_VERSION         86
+
+/* SYS_ABI_VERSION argument and result: (major << 16) | minor, 0 = query */
+#define VERIDIAN_ABI_MAJOR      1
+#define VERIDIAN_ABI_MINOR      5
+
+/* SYS_DMESG flags: low byte selects levels, 0 = all */
+#define DMESG_LEVEL(lvl)        ((lvl) + 1)     /* up to KLOG_* level */
+#define DMESG_CLEAR             0x100           /* clear after reading */
+
+/* Kernel log levels */
+#define KLOG_ERR                0
+#define KLOG_WARN               1
+#define KLOG_INFO               2
+#define KLOG_DEBUG              3
+#define KLOG_TRACE              4
+
+/* SYS_TRACE_CTL operations (root only) */
+#define TRACE_ENABLE            0
+#define TRACE_DISABLE           1
+#define TRACE_SET_MASK          2       /* arg: mask of 1 << TRACE_EV_* */
+#define TRACE_SET_PID           3       /* arg: pid, 0 = all */
+#define TRACE_CLEAR             4
+#define TRACE_READ              5       /* buf, len -> records copied */
+#define TRACE_STATS             6       /* buf, len */
+
+/* Trace event types */
+#define TRACE_EV_SYSCALL_ENTRY  0       /* data: nr, arg1 */
+#define TRACE_EV_SYSCALL_EXIT   1       /* data: nr, return value */
+#define TRACE_EV_SWITCH_OUT     2       /* data: pid, tid */
+#define TRACE_EV_SWITCH_IN      3       /* data: pid, tid */
+#define TRACE_EV_IPC_SEND       4       /* data: target pid, capability */
+#define TRACE_EV_IPC_RECV       5       /* data: endpoint, capability */
+#define TRACE_EV_FRAME_ALLOC    6       /* data: frame number, cpu */
+#define TRACE_EV_FRAME_FREE     7       /* data: frame number, cpu */
+#define TRACE_EV_PAGE_FAULT     8       /* data: address, error code */
+#define TRACE_EV_IPC_SLOW_PATH  9       /* data: target pid, capability */
+#define TRACE_EV_PAGE_FAULT_DONE 10     /* data: address, outcome */
+#define TRACE_EV_COUNT          11
+
+/* SYS_CRASH_DUMP flags (root only) */
+#define CRASH_DUMP_ACK          1       /* mark the dump as seen */
+
+/* SYS_WATCHDOG operations (root only) */
+#define WATCHDOG_ARM            0       /* arg: timeout in ms */
+#define WATCHDOG_PET            1
+#define WATCHDOG_DISARM         2
+#define WATCHDOG_FIRE           3       /* reset the machine now */
+#define WATCHDOG_REMAINING      4       /* -> ms left before the reset */
+/* Or'd into the operation to pick a channel; 0 is init's, 1 is watchdogd's */
+#define WATCHDOG_CHANNEL(n)     ((n) << 8)
+#define WATCHDOG_CHANNELS       4
+
+/* SYS_REBOOT commands (root only; filesystems are synced and unmounted) */
+#define REBOOT_CMD_RESTART      1
+#define REBOOT_CMD_POWER_OFF    2
+#define REBOOT_CMD_HALT         3
+#define REBOOT_CMD_SUSPEND      4       /* returns after resume; needs `suspend` */
+#define REBOOT_CMD_PENDING      5       /* -> command the kernel asks for, or 0 */
+
+/* Package management (90-95) */
 #define SYS_PKG_INSTALL         90
 #define SYS_PKG_REMOVE          91
 #define SYS_PKG_QUERY           92
 #define SYS_PKG_LIST            93
 #define SYS_PKG_UPDATE          94
+#define SYS_PKG_VERIFY          95
+
+/* A/B kernel boot slots (96-98) */
+#define SYS_BOOT_SLOT_INSTALL   96
+#define SYS_BOOT_SLOT_COMMIT    97
+#define SYS_BOOT_SLOT_QUERY     98
 
 /* Time management (100-102) */
 #define SYS_TIME_GET_UPTIME     100
@@ -151,6 +225,10 @@ extern "C" {
 #define SYS_SETSID              179
 #define SYS_GETSID              180
 
+/* Supplementary groups (181-182) */
+#define SYS_GETGROUPS           181
+#define SYS_SETGROUPS           182
+
 /* Scatter/gather I/O (183-184) */
 #define SYS_READV               183
 #define SYS_WRITEV              184
@@ -197,19 +275,98 @@ extern "C" {
 #define SYS_SOCKET_CLOSE        227
 #define SYS_SOCKET_PAIR         228
 
-/* Network extensions / AF_INET (250-255) */
+/* Graphics / framebuffer (230-239) */
+#define SYS_FB_GET_INFO         230
+#define SYS_FB_MAP              231
+#define SYS_INPUT_POLL          232
+#define SYS_INPUT_READ          233
+#define SYS_FB_SWAP             234
+#define SYS_SCREENSHOT          235
+#define SYS_NOTIFY              236
+#define SYS_WINDOW              237     /* see <veridian/window.h> */
+#define SYS_INPUT_INJECT        238     /* see <veridian/input.h> */
+#define SYS_FB_FLIP             239     /* buf, flags -> vblank sequence */
+
+/* SYS_FB_FLIP flags */
+#define FB_FLIP_VSYNC           1       /* present at the next vblank */
+
+/* SYS_SCREENSHOT operations (root only; window 0 = whole screen) */
+#define SCREENSHOT_CAPTURE      0       /* path, window -> bytes written */
+#define SCREENSHOT_RECORD_START 1       /* dir, window, interval, max frames */
+#define SCREENSHOT_RECORD_STOP  2       /* -> frames written */
+#define SCREENSHOT_RECORD_STATUS 3      /* -> frames written so far */
+#define SCREENSHOT_FOCUSED      (-1)    /* window argument: focused window */
+
+/* SYS_NOTIFY(buf, len): buf holds a notification message (see notify-send)
+ * whose first byte is one of these */
+#define NOTIFY_MSG_NOTIFY       0       /* -> notification ID */
+#define NOTIFY_MSG_DISMISS      1       /* ID -> 0 */
+#define NOTIFY_MSG_DISMISS_ALL  2       /* -> 0 */
+#define NOTIFY_MSG_GET_ACTIVE   3       /* -> notifications on screen */
+#define NOTIFY_MSG_STATUS       4       /* ID -> NOTIFY_STATUS_* */
+#define NOTIFY_STATUS_ACTIVE    0       /* still shown */
+#define NOTIFY_STATUS_EXPIRED   1       /* timed out */
+#define NOTIFY_STATUS_DISMISSED 2       /* clicked away */
+#define NOTIFY_STATUS_CLOSED    3       /* closed by a program */
+#define NOTIFY_STATUS_ACTION    0x100   /* + index of the invoked action */
+#define NOTIFY_TIMEOUT_DEFAULT  0xFFFFFFFFu
+#define NOTIFY_MAX_ACTIONS      3
+
+/* Network extensions / AF_INET (250-257) */
 #define SYS_NET_SENDTO          250
 #define SYS_NET_RECVFROM        251
 #define SYS_NET_GETSOCKNAME     252
 #define SYS_NET_GETPEERNAME     253
 #define SYS_NET_SETSOCKOPT      254
 #define SYS_NET_GETSOCKOPT      255
+#define SYS_NET_FIREWALL        256
+#define SYS_NET_PACKET_RING     257     /* see <veridian/pktring.h> */
+
+/* SYS_NET_FIREWALL operations (root only) */
+#define FW_APPEND               0       /* struct fw_rule * -> rule id */
+#define FW_INSERT               1       /* struct fw_rule *, position -> id */
+#define FW_DELETE               2       /* rule id */
+#define FW_FLUSH                3       /* chain, FW_CHAIN_ALL = all */
+#define FW_POLICY               4       /* chain, FW_POLICY_* */
+#define FW_LIST                 5       /* buf, len -> rules copied */
+#define FW_CONNTRACK            6       /* buf, len -> connections copied */
+#define FW_STATS                7       /* buf, len */
+#define FW_ZERO                 8       /* zero the counters */
+
+/* Packet filter chains */
+#define FW_CHAIN_INPUT          0
+#define FW_CHAIN_OUTPUT         1
+#define FW_CHAIN_FORWARD        2
+#define FW_CHAIN_ALL            255
+
+#define FW_POLICY_ACCEPT        0
+#define FW_POLICY_DROP          1
+
+/* Resource limits (260-261) */
+#define SYS_GETRLIMIT           260
+#define SYS_SETRLIMIT           261
 
 /* epoll I/O multiplexing (262-264) */
 #define SYS_EPOLL_CREATE        262
 #define SYS_EPOLL_CTL           263
 #define SYS_EPOLL_WAIT          264
 
+/* Pseudo-terminals (280-283) */
+#define SYS_OPENPTY             280
+#define SYS_GRANTPT             281
+#define SYS_UNLOCKPT            282
+#define SYS_PTSNAME             283
+
+/* Audio PCM streams (320-327) */
+#define SYS_AUDIO_OPEN          320
+#define SYS_AUDIO_CLOSE         321
+#define SYS_AUDIO_WRITE         322
+#define SYS_AUDIO_SET_VOLUME    323
+#define SYS_AUDIO_GET_INFO      324
+#define SYS_AUDIO_START         325
+#define SYS_AUDIO_STOP          326
+#define SYS_AUDIO_PAUSE         327
+
 /* Event/timer notification fds + getrandom (330-339) */
 #define SYS_GETRANDOM           330
 #define SYS_EVENTFD_CREATE      331
@@ -239,6 +396,60 @@ extern "C" {
 #define SYS_SET_ROBUST_LIST     353
 #define SYS_CLOCK_NANOSLEEP    354
 
+/* Credential syscalls: real/effective/saved IDs (355-358) */
+#define SYS_SETREUID            355
+#define SYS_SETREGID            356
+#define SYS_GETRESUID           357
+#define SYS_GETRESGID           358
+
+/* Password hashing (kernel PBKDF2-HMAC-SHA256) */
+#define SYS_CRYPT               359
+
+/* Interval timers and resource usage */
+#define SYS_SETITIMER           360
+#define SYS_GETITIMER           361
+#define SYS_GETRUSAGE           362
+
+/* Cryptographic primitives (kernel/src/syscall/crypto.rs) */
+#define SYS_CRYPTO_HASH         363
+#define SYS_CRYPTO_HMAC         364
+#define SYS_CRYPTO_AEAD_SEAL    365
+#define SYS_CRYPTO_AEAD_OPEN    366
+#define SYS_CRYPTO_X25519       367
+#define SYS_CRYPTO_VERIFY       368
+#define SYS_CRYPTO_SIGN         369
+
+/* In-kernel data transfer */
+#define SYS_SENDFILE            370
+#define SYS_SPLICE              371
+
+/* Extended attributes */
+#define SYS_GETXATTR            372
+#define SYS_SETXATTR            373
+#define SYS_LISTXATTR           374
+#define SYS_REMOVEXATTR         375
+#define SYS_FGETXATTR           376
+#define SYS_FSETXATTR           377
+#define SYS_FLISTXATTR          378
+#define SYS_FREMOVEXATTR        379
+
+/* Space management */
+#define SYS_FALLOCATE           380
+
+/* Process times (struct tms, clock ticks) */
+#define SYS_TIMES               381
+
+/* Heap profiling (struct veridian_heap_stats, <veridian/heap_profile.h>) */
+#define SYS_HEAP_PROFILE        382
+
+/* Syscall filters (<veridian/syscall_filter.h>) */
+#define SYS_SYSCALL_FILTER      383
+
+/* IPC namespace supervision (kernel/src/ipc/namespace.rs) */
+#define SYS_IPC_NAMESPACE_CREATE 384
+#define SYS_IPC_NAMESPACE_ASSIGN 385
+#define SYS_IPC_NAMESPACE_EXPORT 386
+
 /* AT_* constants for *at() syscalls */
 #define AT_FDCWD                (-100)
 #define AT_REMOVEDIR            0x200
@@ -0,0 +1,67 @@
+/*
+ * VeridianOS Syscall Filters
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * SYS_SYSCALL_FILTER(pid, mode, action, entries, count) installs a filter
+ * on the calling process (pid 0) or on one of its children:
+ *
+ *   mode     FILTER_ALLOW: only the listed syscalls pass
+ *            FILTER_DENY:  the listed syscalls are rejected
+ *            | FILTER_ON_EXEC to take effect at the process's next exec
+ *   action   what a rejected syscall does: FILTER_KILL the process,
+ *            fail with EPERM (FILTER_ERRNO), or run and be logged
+ *            (FILTER_LOG)
+ *   entries  uint32_t syscall numbers or FILTER_GROUP_* groups
+ *
+ * Filters stack: every installed filter is checked and the harshest
+ * action wins. They are inherited on fork, kept across exec and can never
+ * be removed. Exiting and returning from a signal handler always pass.
+ * The groups are defined in libs/veridian-abi/src/syscall_filter.rs.
+ */
+
+#ifndef VERIDIAN_SYSCALL_FILTER_H
+#define VERIDIAN_SYSCALL_FILTER_H
+
+#include <veridian/syscall.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Modes */
+#define FILTER_ALLOW            0
+#define FILTER_DENY             1
+#define FILTER_ON_EXEC          0x100
+
+/* Actions */
+#define FILTER_KILL             0
+#define FILTER_ERRNO            1
+#define FILTER_LOG              2
+
+#define FILTER_MAX_ENTRIES      512
+
+/* Syscall groups */
+#define FILTER_GROUP            0x80000000u
+#define FILTER_GROUP_BASIC      (FILTER_GROUP | 0)     /* memory, signals, clocks, I/O on open fds */
+#define FILTER_GROUP_THREAD     (FILTER_GROUP | 1)
+#define FILTER_GROUP_PROCESS    (FILTER_GROUP | 2)     /* fork, exec, wait, kill, sessions */
+#define FILTER_GROUP_CREDENTIALS (FILTER_GROUP | 3)    /* setuid and friends */
+#define FILTER_GROUP_FILE       (FILTER_GROUP | 4)     /* paths, directories, metadata */
+#define FILTER_GROUP_IPC        (FILTER_GROUP | 5)
+#define FILTER_GROUP_NET        (FILTER_GROUP | 6)     /* sockets */
+#define FILTER_GROUP_PTY        (FILTER_GROUP | 7)
+#define FILTER_GROUP_CRYPTO     (FILTER_GROUP | 8)
+#define FILTER_GROUP_GRAPHICS   (FILTER_GROUP | 9)     /* framebuffer, input, Wayland, audio */
+#define FILTER_GROUP_DEBUG      (FILTER_GROUP | 10)    /* ptrace, dmesg, tracing, audit */
+#define FILTER_GROUP_PACKAGES   (FILTER_GROUP | 11)    /* packages and boot slots */
+#define FILTER_GROUP_REBOOT     (FILTER_GROUP | 12)
+#define FILTER_GROUP_WATCHDOG   (FILTER_GROUP | 13)
+#define FILTER_GROUP_ADMIN      (FILTER_GROUP | 14)    /* mount, mknod, firewall, capabilities, ... */
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_SYSCALL_FILTER_H */
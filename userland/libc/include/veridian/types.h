@@ -31,6 +31,9 @@ typedef uint32_t    uid_t;
 /** Group identifier */
 typedef uint32_t    gid_t;
 
+/** Generic identifier, wide enough for pid_t, uid_t and gid_t */
+typedef int64_t     id_t;
+
 /* ========================================================================= */
 /* Filesystem Types                                                          */
 /* ========================================================================= */
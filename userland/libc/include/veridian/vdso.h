@@ -0,0 +1,34 @@
+/*
+ * VeridianOS libc -- <veridian/vdso.h>
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Layout of the vDSO mapped at getauxval(AT_VDSO).  Must match
+ * kernel/src/process/vdso.rs.
+ *
+ * The vDSO is three pages: shared kernel data, per-process data, then
+ * code.  The code page starts with struct veridian_vdso_header, whose
+ * offsets are relative to the code page.  Every entry point returns a
+ * negative errno on failure; -ENOSYS means "make the real syscall".
+ */
+
+#ifndef _VERIDIAN_VDSO_H
+#define _VERIDIAN_VDSO_H
+
+#include <stdint.h>
+
+#define VERIDIAN_VDSO_MAGIC     0x4F534456U /* "VDSO" */
+#define VERIDIAN_VDSO_VERSION   1
+#define VERIDIAN_VDSO_TEXT      0x2000      /* Offset of the code page */
+
+struct veridian_vdso_header {
+    uint32_t magic;
+    uint32_t version;
+    uint32_t clock_gettime;     /* long (int clk_id, struct timespec *) */
+    uint32_t gettid;            /* long (void) */
+    uint32_t sched_yield;       /* long (void) */
+    uint32_t reserved;
+};
+
+#endif /* _VERIDIAN_VDSO_H */
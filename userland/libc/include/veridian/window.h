@@ -0,0 +1,97 @@
+/*
+ * VeridianOS Desktop Windows
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A process opens a top-level window on the desktop with SYS_WINDOW, draws
+ * its content into its own memory and presents whole frames of XRGB8888
+ * pixels (width * height * 4 bytes, 0x00RRGGBB per pixel). The desktop adds
+ * the title bar and the close button.
+ *
+ * Input for the window is queued and read with win_poll(). Pointer
+ * positions are relative to the top-left corner of the content. The close
+ * button only queues WIN_EVENT_CLOSE; the window stays open until the
+ * process calls win_destroy() or exits.
+ *
+ * A window flagged WIN_FLAG_NO_FOCUS (e.g. an on-screen keyboard) stays
+ * above other windows and gets clicks without taking the keyboard focus.
+ */
+
+#ifndef VERIDIAN_WINDOW_H
+#define VERIDIAN_WINDOW_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#include <veridian/syscall.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* SYS_WINDOW operations */
+#define WIN_CREATE              0       /* width, height, title, len -> id */
+#define WIN_DESTROY             1       /* id */
+#define WIN_PRESENT             2       /* id, pixels, bytes */
+#define WIN_POLL                3       /* id, events, max -> count */
+#define WIN_SET_TITLE           4       /* id, title, len */
+#define WIN_SET_FLAGS           5       /* id, flags */
+
+/* WIN_SET_FLAGS bits */
+#define WIN_FLAG_NO_FOCUS       0x1
+
+#define WIN_MAX_DIMENSION       2048
+
+/* win_event.kind */
+#define WIN_EVENT_KEY_PRESS     1       /* code = scancode, x = character */
+#define WIN_EVENT_KEY_RELEASE   2       /* code = scancode */
+#define WIN_EVENT_BUTTON_PRESS  3       /* code = button (0 left, 1 right) */
+#define WIN_EVENT_BUTTON_RELEASE 4      /* code = button */
+#define WIN_EVENT_MOTION        5
+#define WIN_EVENT_SCROLL        6       /* x, y = scroll delta */
+#define WIN_EVENT_CLOSE         7       /* close button clicked */
+
+struct win_event {
+    uint32_t kind;
+    uint32_t code;
+    int32_t x;
+    int32_t y;
+};
+
+static inline long win_create(uint32_t width, uint32_t height, const char *title)
+{
+    return veridian_syscall5(SYS_WINDOW, WIN_CREATE, width, height, title, strlen(title));
+}
+
+static inline long win_destroy(long win)
+{
+    return veridian_syscall2(SYS_WINDOW, WIN_DESTROY, win);
+}
+
+static inline long win_present(long win, const uint32_t *pixels, size_t bytes)
+{
+    return veridian_syscall4(SYS_WINDOW, WIN_PRESENT, win, pixels, bytes);
+}
+
+static inline long win_poll(long win, struct win_event *events, size_t max)
+{
+    return veridian_syscall4(SYS_WINDOW, WIN_POLL, win, events, max);
+}
+
+static inline long win_set_title(long win, const char *title)
+{
+    return veridian_syscall4(SYS_WINDOW, WIN_SET_TITLE, win, title, strlen(title));
+}
+
+static inline long win_set_flags(long win, uint32_t flags)
+{
+    return veridian_syscall3(SYS_WINDOW, WIN_SET_FLAGS, win, flags);
+}
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VERIDIAN_WINDOW_H */
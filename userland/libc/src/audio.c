@@ -0,0 +1,218 @@
+/*
+ * VeridianOS libc -- audio.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * PCM playback on top of the kernel audio streams, see <veridian/audio.h>.
+ * Kernel syscalls: AudioOpen=320 .. AudioPause=327.
+ */
+
+#include <veridian/audio.h>
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+#include <veridian/syscall.h>
+
+struct veridian_pcm {
+    long stream;
+    unsigned channels;
+    int16_t *ring;
+    size_t ring_frames;
+    size_t head;        /* Next frame to hand to the kernel */
+    size_t queued;      /* Frames in the ring */
+};
+
+/*
+ * Translate raw syscall return to POSIX convention.
+ * Negative values become errno + return -1.
+ */
+static inline long __audio_ret(long r)
+{
+    if (r < 0) {
+        errno = (int)(-r);
+        return -1;
+    }
+    return r;
+}
+
+int veridian_audio_info(struct veridian_audio_info *info)
+{
+    if (!info) {
+        errno = EINVAL;
+        return -1;
+    }
+    return (int)__audio_ret(veridian_syscall1(SYS_AUDIO_GET_INFO, info));
+}
+
+veridian_pcm *veridian_pcm_open(unsigned channels, size_t ring_frames)
+{
+    veridian_pcm *pcm;
+    long stream;
+
+    if (channels != 1 && channels != 2) {
+        errno = EINVAL;
+        return NULL;
+    }
+    if (ring_frames == 0)
+        ring_frames = VERIDIAN_PCM_RING_FRAMES;
+
+    pcm = (veridian_pcm *)calloc(1, sizeof(*pcm));
+    if (!pcm)
+        return NULL;
+    pcm->ring = (int16_t *)malloc(ring_frames * channels * sizeof(int16_t));
+    if (!pcm->ring) {
+        free(pcm);
+        return NULL;
+    }
+    pcm->channels = channels;
+    pcm->ring_frames = ring_frames;
+
+    stream = __audio_ret(veridian_syscall2(SYS_AUDIO_OPEN,
+                                           VERIDIAN_PCM_RATE, channels));
+    if (stream < 0)
+        goto fail;
+    pcm->stream = stream;
+    if (__audio_ret(veridian_syscall1(SYS_AUDIO_START, stream)) < 0) {
+        veridian_syscall1(SYS_AUDIO_CLOSE, stream);
+        goto fail;
+    }
+    return pcm;
+
+fail:
+    free(pcm->ring);
+    free(pcm);
+    return NULL;
+}
+
+/* Hand the kernel as many queued frames as it takes. */
+static int __pcm_push(veridian_pcm *pcm)
+{
+    while (pcm->queued > 0) {
+        size_t run = pcm->ring_frames - pcm->head;
+        long written;
+
+        if (run > pcm->queued)
+            run = pcm->queued;
+        written = __audio_ret(veridian_syscall3(
+            SYS_AUDIO_WRITE, pcm->stream,
+            pcm->ring + pcm->head * pcm->channels, run * pcm->channels));
+        if (written < 0)
+            return -1;
+
+        written /= pcm->channels;
+        pcm->head = (pcm->head + (size_t)written) % pcm->ring_frames;
+        pcm->queued -= (size_t)written;
+        if ((size_t)written < run)
+            break;
+    }
+    return 0;
+}
+
+long veridian_pcm_write(veridian_pcm *pcm, const int16_t *samples,
+                        size_t frames)
+{
+    size_t done = 0;
+
+    if (!pcm || (frames > 0 && !samples)) {
+        errno = EINVAL;
+        return -1;
+    }
+
+    /* Make room first, so a short kernel buffer doesn't cut the write */
+    if (__pcm_push(pcm) < 0)
+        return -1;
+
+    while (done < frames && pcm->queued < pcm->ring_frames) {
+        size_t tail = (pcm->head + pcm->queued) % pcm->ring_frames;
+        size_t run = pcm->ring_frames - tail;
+        size_t room = pcm->ring_frames - pcm->queued;
+
+        if (run > room)
+            run = room;
+        if (run > frames - done)
+            run = frames - done;
+        memcpy(pcm->ring + tail * pcm->channels,
+               samples + done * pcm->channels,
+               run * pcm->channels * sizeof(int16_t));
+        pcm->queued += run;
+        done += run;
+    }
+
+    if (__pcm_push(pcm) < 0)
+        return -1;
+    return (long)done;
+}
+
+size_t veridian_pcm_avail(const veridian_pcm *pcm)
+{
+    return pcm ? pcm->ring_frames - pcm->queued : 0;
+}
+
+int veridian_pcm_drain(veridian_pcm *pcm)
+{
+    if (!pcm) {
+        errno = EINVAL;
+        return -1;
+    }
+    for (;;) {
+        if (__pcm_push(pcm) < 0)
+            return -1;
+        if (pcm->queued == 0)
+            return 0;
+        veridian_syscall0(SYS_PROCESS_YIELD);
+    }
+}
+
+int veridian_pcm_set_volume(veridian_pcm *pcm, unsigned volume)
+{
+    if (!pcm || volume > 100) {
+        errno = EINVAL;
+        return -1;
+    }
+    return (int)__audio_ret(veridian_syscall2(SYS_AUDIO_SET_VOLUME,
+                                              pcm->stream, volume));
+}
+
+int veridian_pcm_pause(veridian_pcm *pcm)
+{
+    if (!pcm) {
+        errno = EINVAL;
+        return -1;
+    }
+    return (int)__audio_ret(veridian_syscall1(SYS_AUDIO_PAUSE, pcm->stream));
+}
+
+int veridian_pcm_resume(veridian_pcm *pcm)
+{
+    if (!pcm) {
+        errno = EINVAL;
+        return -1;
+    }
+    return (int)__audio_ret(veridian_syscall1(SYS_AUDIO_START, pcm->stream));
+}
+
+int veridian_pcm_stop(veridian_pcm *pcm)
+{
+    if (!pcm) {
+        errno = EINVAL;
+        return -1;
+    }
+    pcm->head = 0;
+    pcm->queued = 0;
+    return (int)__audio_ret(veridian_syscall1(SYS_AUDIO_STOP, pcm->stream));
+}
+
+int veridian_pcm_close(veridian_pcm *pcm)
+{
+    long ret;
+
+    if (!pcm) {
+        errno = EINVAL;
+        return -1;
+    }
+    ret = __audio_ret(veridian_syscall1(SYS_AUDIO_CLOSE, pcm->stream));
+    free(pcm->ring);
+    free(pcm);
+    return (int)ret;
+}
@@ -0,0 +1,97 @@
+/*
+ * VeridianOS libc -- auxv.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Auxiliary vector and vDSO lookup.
+ *
+ * __libc_init() hands us envp; the auxiliary vector follows its NULL
+ * terminator.  getauxval() reads it, and the vDSO entry points named by
+ * AT_VDSO are resolved once so that clock_gettime(), gettid() and
+ * sched_yield() can skip the syscall.
+ */
+
+#include <sys/auxv.h>
+#include <veridian/vdso.h>
+#include <errno.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct {
+    unsigned long a_type;
+    unsigned long a_val;
+} auxv_entry_t;
+
+static const auxv_entry_t *__auxv;
+
+/* vDSO entry points, NULL when the kernel did not map one. */
+long (*__vdso_clock_gettime)(int, void *);
+long (*__vdso_gettid)(void);
+long (*__vdso_sched_yield)(void);
+
+/*
+ * Locate the auxiliary vector and resolve the vDSO.
+ *
+ * @param envp  Environment array from the initial stack.
+ */
+void __libc_init_auxv(char **envp)
+{
+    while (*envp)
+        envp++;
+    __auxv = (const auxv_entry_t *)(envp + 1);
+
+    uintptr_t base = 0;
+    for (const auxv_entry_t *a = __auxv; a->a_type != AT_NULL; a++) {
+        if (a->a_type == AT_VDSO)
+            base = a->a_val;
+    }
+    if (base == 0)
+        return;
+
+    uintptr_t text = base + VERIDIAN_VDSO_TEXT;
+    const struct veridian_vdso_header *hdr =
+        (const struct veridian_vdso_header *)text;
+    if (hdr->magic != VERIDIAN_VDSO_MAGIC ||
+        hdr->version != VERIDIAN_VDSO_VERSION)
+        return;
+
+    __vdso_clock_gettime = (long (*)(int, void *))(text + hdr->clock_gettime);
+    __vdso_gettid = (long (*)(void))(text + hdr->gettid);
+    __vdso_sched_yield = (long (*)(void))(text + hdr->sched_yield);
+}
+
+/*
+ * Fallbacks for programs started without an auxiliary vector (older
+ * kernels).  Deterministic but non-zero bytes stand in for AT_RANDOM.
+ */
+static uint8_t __at_random_bytes[16] = {
+    0x4f, 0x89, 0xc3, 0x7a, 0x15, 0xde, 0x62, 0xb8,
+    0x91, 0x3d, 0xa7, 0x05, 0xf4, 0x28, 0x6c, 0xe0
+};
+
+unsigned long getauxval(unsigned long type)
+{
+    if (__auxv) {
+        for (const auxv_entry_t *a = __auxv; a->a_type != AT_NULL; a++) {
+            if (a->a_type == type)
+                return a->a_val;
+        }
+    }
+
+    switch (type) {
+    case AT_RANDOM:
+        return (unsigned long)__at_random_bytes;
+    case AT_PAGESZ:
+        return 4096;
+    case AT_CLKTCK:
+        return 100;  /* HZ = 100 */
+    case AT_HWCAP:
+    case AT_HWCAP2:
+    case AT_SECURE:
+        return 0;
+    default:
+        errno = ENOENT;
+        return 0;
+    }
+}
@@ -620,9 +620,13 @@ struct passwd *getpwent(void)
     return (struct passwd *)0;
 }
 
+extern char *getenv(const char *name);
+
+/* login(1) exports LOGNAME for the session; fall back to root. */
 char *getlogin(void)
 {
-    return "root";
+    char *name = getenv("LOGNAME");
+    return (name && *name) ? name : "root";
 }
 
 /* ========================================================================= */
@@ -14,6 +14,7 @@
 #include <stdlib.h>
 #include <string.h>
 #include <sys/types.h>
+#include <sys/time.h>
 
 /* sigset_t is not pulled in by the above headers. */
 typedef unsigned long sigset_t;
@@ -407,9 +408,16 @@ int utimes(const char *filename, const void *times)
 
 unsigned int alarm(unsigned int seconds)
 {
-    (void)seconds;
-    /* Stub: no alarm/signal delivery yet */
-    return 0;
+    struct itimerval new_value, old_value;
+
+    memset(&new_value, 0, sizeof(new_value));
+    new_value.it_value.tv_sec = (long)seconds;
+    if (setitimer(ITIMER_REAL, &new_value, &old_value) < 0)
+        return 0;
+
+    /* Round any pending fraction up so a live alarm never reports 0. */
+    return (unsigned int)old_value.it_value.tv_sec +
+           (old_value.it_value.tv_usec > 0 ? 1 : 0);
 }
 
 /* ========================================================================= */
@@ -638,6 +646,8 @@ int getnameinfo(const struct sockaddr *sa, socklen_t salen,
  *   SYS_NET_GETPEERNAME(253) -- getpeername()
  *   SYS_NET_SETSOCKOPT (254) -- setsockopt()
  *   SYS_NET_GETSOCKOPT (255) -- getsockopt()
+ *   SYS_SENDMSG        (338) -- sendmsg()
+ *   SYS_RECVMSG        (339) -- recvmsg()
  */
 
 #include <veridian/syscall.h>
@@ -676,7 +686,7 @@ int socket(int domain, int type, int protocol)
  *
  * Kernel args: (socket_id, addr_ptr, addr_len)
  */
-int connect(int sockfd, const void *addr, unsigned int addrlen)
+int connect(int sockfd, const struct sockaddr *addr, socklen_t addrlen)
 {
     long ret = veridian_syscall3(SYS_SOCKET_CONNECT, sockfd, addr, addrlen);
     return (int)__sock_ret(ret);
@@ -687,7 +697,7 @@ int connect(int sockfd, const void *addr, unsigned int addrlen)
  *
  * Kernel args: (socket_id, addr_ptr, addr_len)
  */
-int bind(int sockfd, const void *addr, unsigned int addrlen)
+int bind(int sockfd, const struct sockaddr *addr, socklen_t addrlen)
 {
     long ret = veridian_syscall3(SYS_SOCKET_BIND, sockfd, addr, addrlen);
     return (int)__sock_ret(ret);
@@ -705,54 +715,50 @@ int listen(int sockfd, int backlog)
 }
 
 /*
- * accept() -- accept a connection on a socket.
+ * accept4() -- accept a connection on a socket.
  *
- * The kernel's SYS_SOCKET_ACCEPT takes only the listening socket_id and
- * returns the new connected socket_id.  addr/addrlen output is not filled
- * by the kernel at this time; we zero them out to avoid stale data.
- *
- * Kernel args: (socket_id)
+ * Kernel args: (fd, addr_ptr, addrlen_ptr, flags)
+ * Returns a new descriptor for the connection; flags may hold
+ * SOCK_NONBLOCK and SOCK_CLOEXEC.  When addr and addrlen are given the
+ * kernel writes the peer address: a 16-byte sockaddr_in for AF_INET, or a
+ * sockaddr_un truncated to *addrlen (which it updates) for AF_UNIX.
  */
-int accept(int sockfd, void *addr, unsigned int *addrlen)
+int accept4(int sockfd, struct sockaddr *addr, socklen_t *addrlen, int flags)
 {
-    long ret = veridian_syscall1(SYS_SOCKET_ACCEPT, sockfd);
-    if (ret < 0) {
-        errno = (int)(-ret);
-        return -1;
-    }
-    /* Clear addr output so callers don't see stale data. */
-    if (addr && addrlen && *addrlen > 0) {
-        unsigned int i;
-        for (i = 0; i < *addrlen; i++)
-            ((unsigned char *)addr)[i] = 0;
-        *addrlen = 0;
-    }
-    return (int)ret;
+    long ret;
+    if (addr && addrlen)
+        ret = veridian_syscall4(SYS_SOCKET_ACCEPT, sockfd, addr, addrlen, flags);
+    else
+        ret = veridian_syscall4(SYS_SOCKET_ACCEPT, sockfd, 0, 0, flags);
+    return (int)__sock_ret(ret);
+}
+
+int accept(int sockfd, struct sockaddr *addr, socklen_t *addrlen)
+{
+    return accept4(sockfd, addr, addrlen, 0);
 }
 
 /*
  * send() -- send a message on a socket.
  *
- * Kernel args: (socket_id, buf_ptr, buf_len)
- * The flags argument is not forwarded (not yet supported by the kernel).
+ * Kernel args: (fd, buf_ptr, buf_len, flags)
+ * MSG_DONTWAIT makes this one call non-blocking.
  */
 long send(int sockfd, const void *buf, unsigned long len, int flags)
 {
-    (void)flags;
-    long ret = veridian_syscall3(SYS_SOCKET_SEND, sockfd, buf, len);
+    long ret = veridian_syscall4(SYS_SOCKET_SEND, sockfd, buf, len, flags);
     return __sock_ret(ret);
 }
 
 /*
  * recv() -- receive a message from a socket.
  *
- * Kernel args: (socket_id, buf_ptr, buf_len)
- * The flags argument is not forwarded (not yet supported by the kernel).
+ * Kernel args: (fd, buf_ptr, buf_len, flags)
+ * MSG_DONTWAIT makes this one call non-blocking.
  */
 long recv(int sockfd, void *buf, unsigned long len, int flags)
 {
-    (void)flags;
-    long ret = veridian_syscall3(SYS_SOCKET_RECV, sockfd, buf, len);
+    long ret = veridian_syscall4(SYS_SOCKET_RECV, sockfd, buf, len, flags);
     return __sock_ret(ret);
 }
 
@@ -772,46 +778,112 @@ int setsockopt(int sockfd, int level, int optname,
 /*
  * getsockopt() -- get options on sockets.
  *
- * Kernel args: (fd, level, optname, optval_ptr)
- * The optlen pointer is not forwarded; the kernel writes a fixed 4-byte value.
+ * Kernel args: (fd, level, optname, optval_ptr, optlen_ptr)
+ * The kernel checks *optlen against the option's size and stores the size
+ * it wrote there.
  */
 int getsockopt(int sockfd, int level, int optname,
                void *optval, unsigned int *optlen)
 {
-    long ret = veridian_syscall4(SYS_NET_GETSOCKOPT,
-                                  sockfd, level, optname, optval);
-    if (ret < 0) {
-        errno = (int)(-ret);
-        return -1;
-    }
-    /* Kernel writes 4 bytes; reflect that in *optlen if provided. */
-    if (optlen)
-        *optlen = 4;
-    return 0;
+    long ret = veridian_syscall5(SYS_NET_GETSOCKOPT,
+                                  sockfd, level, optname, optval, optlen);
+    return (int)__sock_ret(ret);
+}
+
+/*
+ * sendmsg() -- send a message with ancillary data.
+ *
+ * Kernel args: (socket_id, msghdr_ptr, flags)
+ * SCM_RIGHTS descriptors in msg_control are passed to the receiving
+ * process on AF_UNIX sockets; msg_name addresses AF_UNIX datagrams.
+ */
+ssize_t sendmsg(int sockfd, const struct msghdr *msg, int flags)
+{
+    long ret = veridian_syscall3(SYS_SENDMSG, sockfd, msg, flags);
+    return __sock_ret(ret);
+}
+
+/*
+ * recvmsg() -- receive a message with ancillary data.
+ *
+ * Kernel args: (socket_id, msghdr_ptr, flags)
+ * The kernel updates msg_namelen, msg_controllen and msg_flags (MSG_CTRUNC
+ * when received descriptors did not fit in msg_control).
+ */
+ssize_t recvmsg(int sockfd, struct msghdr *msg, int flags)
+{
+    long ret = veridian_syscall3(SYS_RECVMSG, sockfd, msg, flags);
+    return __sock_ret(ret);
 }
 
 /*
  * shutdown() -- shut down part of a full-duplex connection.
  *
- * The kernel does not yet have a dedicated shutdown syscall.  We use
- * SYS_SOCKET_CLOSE as a best-effort implementation when SHUT_RDWR is
- * requested.  For SHUT_RD / SHUT_WR we return 0 without action (the
- * kernel's socket layer does not support half-close at this time).
+ * The kernel does not yet have a dedicated shutdown syscall, and the
+ * descriptor must stay open until close() (which releases the socket), so
+ * this succeeds without action.
  */
 int shutdown(int sockfd, int how)
 {
-#define SHUT_RD_LOCAL   0
-#define SHUT_WR_LOCAL   1
-#define SHUT_RDWR_LOCAL 2
-    if (how == SHUT_RDWR_LOCAL) {
-        long ret = veridian_syscall1(SYS_SOCKET_CLOSE, sockfd);
-        return (int)__sock_ret(ret);
-    }
-    /* Half-shutdown not supported; succeed silently. */
     (void)sockfd;
+    (void)how;
     return 0;
 }
 
+/* ========================================================================= */
+/* Interface name <-> index (<net/if.h>)                                     */
+/* ========================================================================= */
+
+#include <net/if.h>
+#include <sys/ioctl.h>
+#include <unistd.h>
+
+/*
+ * Both lookups go through SIOCGIFINDEX / SIOCGIFNAME on a throwaway
+ * AF_INET datagram socket, the same way glibc does it.
+ */
+static int __ifreq_ioctl(unsigned long request, struct ifreq *ifr)
+{
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0)
+        return -1;
+    int ret = ioctl(sock, request, ifr);
+    int saved = errno;
+    close(sock);
+    errno = saved;
+    return ret;
+}
+
+unsigned int if_nametoindex(const char *ifname)
+{
+    struct ifreq ifr;
+
+    if (!ifname || strlen(ifname) >= IFNAMSIZ) {
+        errno = ENODEV;
+        return 0;
+    }
+    memset(&ifr, 0, sizeof(ifr));
+    strcpy(ifr.ifr_name, ifname);
+    if (__ifreq_ioctl(SIOCGIFINDEX, &ifr) < 0)
+        return 0;
+    return (unsigned int)ifr.ifr_ifindex;
+}
+
+char *if_indextoname(unsigned int ifindex, char *ifname)
+{
+    struct ifreq ifr;
+
+    memset(&ifr, 0, sizeof(ifr));
+    ifr.ifr_ifindex = (int)ifindex;
+    if (__ifreq_ioctl(SIOCGIFNAME, &ifr) < 0) {
+        errno = ENXIO;
+        return NULL;
+    }
+    memcpy(ifname, ifr.ifr_name, IFNAMSIZ);
+    ifname[IFNAMSIZ - 1] = '\0';
+    return ifname;
+}
+
 /* ========================================================================= */
 /* Math functions (libm)                                                     */
 /* ========================================================================= */
@@ -306,29 +306,9 @@ int mkfifo(const char *pathname, mode_t mode)
     return -1;
 }
 
-/* seteuid / setegid / setreuid / setregid / getgroups / setgroups */
-int seteuid(uid_t uid)   { (void)uid; return 0; }
-int setegid(gid_t gid)   { (void)gid; return 0; }
-int setreuid(uid_t ruid, uid_t euid) { (void)ruid; (void)euid; return 0; }
-int setregid(gid_t rgid, gid_t egid) { (void)rgid; (void)egid; return 0; }
-int getgroups(int size, gid_t list[]) { (void)size; (void)list; return 0; }
-int setgroups(size_t size, const gid_t *list) { (void)size; (void)list; return 0; }
+/* seteuid / setegid / setreuid / setregid / getgroups / setgroups: syscall.c */
 
-/* dup3() -- dup2 with flags */
-int dup3(int oldfd, int newfd, int flags)
-{
-    (void)flags;
-    extern int dup2(int, int);
-    return dup2(oldfd, newfd);
-}
-
-/* pipe2() -- pipe with flags */
-int pipe2(int pipefd[2], int flags)
-{
-    (void)flags;
-    extern int pipe(int[2]);
-    return pipe(pipefd);
-}
+/* dup3() / pipe2(): syscall.c */
 
 /* clock_gettime() -- already defined in time.c, not duplicated here */
 #include <time.h>
@@ -736,6 +716,7 @@ int fseeko(FILE *stream, off_t offset, int whence)
 /* --- Group database ------------------------------------------------------ */
 
 #include <grp.h>
+#include <limits.h>
 #include <unistd.h>
 #include <fcntl.h>
 
@@ -933,16 +914,44 @@ struct group *getgrent(void)
     return (struct group *)0;
 }
 
+/*
+ * getgrouplist: `group` followed by every /etc/group entry listing `user`
+ * as a member.  On overflow the required count is stored in *ngroups and
+ * -1 is returned (glibc semantics).
+ */
 int getgrouplist(const char *user, gid_t group, gid_t *groups, int *ngroups)
 {
-    (void)user;
-    if (groups && *ngroups >= 1) {
-        groups[0] = group;
-        *ngroups = 1;
-        return 1;
+    int max = *ngroups;
+    int count = 0;
+    struct group *gr;
+    int fd;
+
+    if (groups && count < max)
+        groups[count] = group;
+    count++;
+
+    fd = open("/etc/group", O_RDONLY);
+    if (fd >= 0) {
+        while (__read_group_line(fd) > 0) {
+            if (!__parse_group_line(&_parsed_gr))
+                continue;
+            gr = &_parsed_gr;
+            if (gr->gr_gid == group)
+                continue;
+            for (char **m = gr->gr_mem; m && *m; m++) {
+                if (strcmp(*m, user) == 0) {
+                    if (groups && count < max)
+                        groups[count] = gr->gr_gid;
+                    count++;
+                    break;
+                }
+            }
+        }
+        close(fd);
     }
-    *ngroups = 1;
-    return -1;
+
+    *ngroups = count;
+    return count > max ? -1 : count;
 }
 
 /* --- Time functions ------------------------------------------------------ */
@@ -977,7 +986,6 @@ char *strptime(const char *s, const char *format, struct tm *tm)
 /* --- System functions ---------------------------------------------------- */
 
 #include <sys/sysinfo.h>
-#include <sys/times.h>
 #include <sched.h>
 
 int sysinfo(struct sysinfo *info)
@@ -995,17 +1003,6 @@ int sysinfo(struct sysinfo *info)
     return 0;
 }
 
-clock_t times(struct tms *buf)
-{
-    if (buf) {
-        buf->tms_utime = 0;
-        buf->tms_stime = 0;
-        buf->tms_cutime = 0;
-        buf->tms_cstime = 0;
-    }
-    return (clock_t)0;
-}
-
 int sched_getaffinity(pid_t pid, size_t cpusetsize, cpu_set_t *mask)
 {
     (void)pid;
@@ -1523,7 +1520,8 @@ struct hostent *gethostbyname(const char *name)
  * getpeername() -- get name of connected peer socket.
  *
  * Kernel syscall SYS_NET_GETPEERNAME (253): (fd, addr_ptr, len_ptr)
- * The kernel writes the remote address into *addr and sets *addrlen to 16.
+ * The kernel writes the remote address into *addr and sets *addrlen to its
+ * length (16 for AF_INET; AF_UNIX addresses are truncated to *addrlen).
  */
 int getpeername(int sockfd, struct sockaddr *addr, socklen_t *addrlen)
 {
@@ -1539,7 +1537,8 @@ int getpeername(int sockfd, struct sockaddr *addr, socklen_t *addrlen)
  * getsockname() -- get socket name (local bound address).
  *
  * Kernel syscall SYS_NET_GETSOCKNAME (252): (fd, addr_ptr, len_ptr)
- * The kernel writes the local address into *addr and sets *addrlen to 16.
+ * The kernel writes the local address into *addr and sets *addrlen to its
+ * length (16 for AF_INET; AF_UNIX addresses are truncated to *addrlen).
  */
 int getsockname(int sockfd, struct sockaddr *addr, socklen_t *addrlen)
 {
@@ -1570,21 +1569,22 @@ char *inet_ntoa(struct in_addr in)
 /*
  * sendto() -- send a message to a specific destination address.
  *
- * Kernel syscall SYS_NET_SENDTO (250): (fd, buf_ptr, buf_len, addr_ptr, addr_len)
- * The flags argument is not forwarded (not yet implemented in the kernel).
- * When dest_addr is NULL (connected socket), falls back to SYS_SOCKET_SEND.
+ * Kernel syscall SYS_NET_SENDTO (250): (fd, buf_ptr, buf_len, flags, addr_ptr)
+ * The kernel infers the address length from its family, so addrlen is not
+ * forwarded.  When dest_addr is NULL (connected socket), falls back to
+ * SYS_SOCKET_SEND.
  */
 ssize_t sendto(int sockfd, const void *buf, size_t len, int flags,
                const struct sockaddr *dest_addr, socklen_t addrlen)
 {
-    (void)flags;
+    (void)addrlen;
     long ret;
     if (dest_addr != NULL) {
         ret = veridian_syscall5(SYS_NET_SENDTO,
-                                 sockfd, buf, len, dest_addr, addrlen);
+                                 sockfd, buf, len, flags, dest_addr);
     } else {
         /* No destination address: send on connected socket. */
-        ret = veridian_syscall3(SYS_SOCKET_SEND, sockfd, buf, len);
+        ret = veridian_syscall4(SYS_SOCKET_SEND, sockfd, buf, len, flags);
     }
     if (ret < 0) {
         errno = (int)(-ret);
@@ -1596,56 +1596,65 @@ ssize_t sendto(int sockfd, const void *buf, size_t len, int flags,
 /*
  * recvfrom() -- receive a message and optionally capture sender's address.
  *
- * Kernel syscall SYS_NET_RECVFROM (251): (fd, buf_ptr, buf_len, addr_ptr)
- * addr_ptr may be NULL (kernel handles gracefully).  addrlen is updated to
- * the fixed sockaddr size (16) when an address was written.
- * The flags argument is not forwarded.
+ * Kernel syscall SYS_NET_RECVFROM (251): (fd, buf_ptr, buf_len, flags, addr_ptr)
+ * The kernel does not know the size of the caller's address buffer, so it
+ * writes into a local sockaddr_storage that is then copied out, truncated
+ * to *addrlen; *addrlen is set to the full address length.
  */
 ssize_t recvfrom(int sockfd, void *buf, size_t len, int flags,
                  struct sockaddr *src_addr, socklen_t *addrlen)
 {
-    (void)flags;
-    long ret = veridian_syscall4(SYS_NET_RECVFROM,
-                                  sockfd, buf, len, src_addr);
+    struct sockaddr_storage from;
+    int want_addr = src_addr != NULL && addrlen != NULL;
+
+    memset(&from, 0, sizeof(from));
+    long ret = veridian_syscall5(SYS_NET_RECVFROM,
+                                  sockfd, buf, len, flags,
+                                  want_addr ? &from : NULL);
     if (ret < 0) {
         errno = (int)(-ret);
         return -1;
     }
-    /* If the caller requested the source address and we wrote one, update
-     * addrlen to reflect the actual sockaddr size the kernel wrote. */
-    if (src_addr != NULL && addrlen != NULL)
-        *addrlen = (socklen_t)sizeof(struct sockaddr);
+    if (want_addr) {
+        socklen_t full = sizeof(struct sockaddr_in);
+        if (from.ss_family == AF_UNIX) {
+            const char *path = (const char *)&from + sizeof(sa_family_t);
+            size_t n = strnlen(path, sizeof(from) - sizeof(sa_family_t));
+            full = (socklen_t)(sizeof(sa_family_t) + (n ? n + 1 : 0));
+        }
+        memcpy(src_addr, &from, full < *addrlen ? full : *addrlen);
+        *addrlen = full;
+    }
     return (ssize_t)ret;
 }
 
 /*
  * socketpair() -- create a pair of connected sockets (AF_UNIX only).
  *
- * Kernel syscall SYS_SOCKET_PAIR (228): (domain, result_ptr)
- * result_ptr points to a two-element u64 array; the kernel writes both
- * socket IDs.  We then copy those 64-bit IDs to the int sv[2] array.
- * type and protocol are not forwarded (kernel creates SOCK_STREAM AF_UNIX
- * pairs by default).
+ * Kernel syscall SYS_SOCKET_PAIR (228): (domain, type, sv)
+ * type is SOCK_STREAM or SOCK_DGRAM; the kernel writes both socket IDs to
+ * sv.  protocol is not forwarded.
  */
 int socketpair(int domain, int type, int protocol, int sv[2])
 {
-    (void)type; (void)protocol;
-    unsigned long ids[2] = {0, 0};
-    long ret = veridian_syscall2(SYS_SOCKET_PAIR, domain, ids);
+    (void)protocol;
+    long ret = veridian_syscall3(SYS_SOCKET_PAIR, domain, type, sv);
     if (ret < 0) {
         errno = (int)(-ret);
         return -1;
     }
-    sv[0] = (int)ids[0];
-    sv[1] = (int)ids[1];
     return 0;
 }
 
 int initgroups(const char *user, gid_t group)
 {
-    (void)user;
-    (void)group;
-    return 0;
+    gid_t groups[NGROUPS_MAX];
+    int n = NGROUPS_MAX;
+
+    /* Silently truncate to the kernel limit, as other libcs do */
+    if (getgrouplist(user, group, groups, &n) < 0)
+        n = NGROUPS_MAX;
+    return setgroups((size_t)n, groups);
 }
 
 /* endgrent() is implemented above with setgrent()/getgrent(). */
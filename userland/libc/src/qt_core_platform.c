@@ -5,10 +5,11 @@
  * SPDX-License-Identifier: MIT OR Apache-2.0
  *
  * OS-level shims for QtCore subsystems that need platform hooks beyond
- * standard POSIX.  Provides eventfd, timerfd, inotify, madvise, and
- * getauxval implementations.
+ * standard POSIX.  Provides eventfd, timerfd, inotify and madvise
+ * implementations.
  *
  * Already implemented elsewhere in the libc:
+ *   - getauxval              (auxv.c)
  *   - epoll_create/ctl/wait  (epoll.c)
  *   - clock_gettime          (time.c)
  *   - posix_memalign         (stdlib.c)
@@ -185,54 +186,6 @@ int madvise(void *addr, size_t length, int advice)
     return 0;
 }
 
-/* ========================================================================= */
-/* getauxval                                                                 */
-/* ========================================================================= */
-
-/*
- * Auxiliary vector types used by QtCore and other programs.
- */
-#define AT_NULL         0   /* End of vector */
-#define AT_RANDOM       25  /* Address of 16 random bytes */
-#define AT_PAGESZ       6   /* System page size */
-#define AT_CLKTCK       17  /* Clock ticks per second */
-#define AT_HWCAP        16  /* Hardware capabilities */
-#define AT_HWCAP2       26  /* Extended hardware capabilities */
-#define AT_SECURE       23  /* Secure mode (setuid) */
-#define AT_EXECFN       31  /* Filename of program */
-
-/*
- * Static random bytes for AT_RANDOM.  In a full implementation the
- * dynamic linker (ld-veridian) would pass the auxv from the kernel.
- * For now, provide deterministic but non-zero bytes.  The kernel's
- * CSPRNG seeds these at process start.
- */
-static uint8_t __at_random_bytes[16] = {
-    0x4f, 0x89, 0xc3, 0x7a, 0x15, 0xde, 0x62, 0xb8,
-    0x91, 0x3d, 0xa7, 0x05, 0xf4, 0x28, 0x6c, 0xe0
-};
-
-unsigned long getauxval(unsigned long type)
-{
-    switch (type) {
-    case AT_RANDOM:
-        return (unsigned long)__at_random_bytes;
-    case AT_PAGESZ:
-        return 4096;
-    case AT_CLKTCK:
-        return 100;  /* HZ = 100 */
-    case AT_HWCAP:
-        return 0;
-    case AT_HWCAP2:
-        return 0;
-    case AT_SECURE:
-        return 0;
-    default:
-        errno = ENOENT;
-        return 0;
-    }
-}
-
 /* ========================================================================= */
 /* memfd_create                                                              */
 /* ========================================================================= */
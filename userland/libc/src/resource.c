@@ -4,15 +4,30 @@
  * Copyright (c) 2025-2026 VeridianOS Contributors
  * SPDX-License-Identifier: MIT OR Apache-2.0
  *
- * Stub implementations of resource limit and usage functions.
- * getrlimit() returns RLIM_INFINITY for all resources.
- * setrlimit() is a no-op returning 0.
- * getrusage() zeroes the output structure.
+ * Userland wrappers for the per-process resource limit system calls.
+ * Kernel syscalls: GetRlimit=260, SetRlimit=261.
+ * getpriority()/setpriority() translate nice values to the kernel's
+ * priority scale (SYS_PROCESS_GETPRIORITY/SETPRIORITY = 18/17), where 100
+ * is normal and lower is more urgent.
+ * getrusage() is a real syscall wrapper in syscall.c.
  */
 
 #include <sys/resource.h>
-#include <string.h>
 #include <errno.h>
+#include <veridian/syscall.h>
+
+/*
+ * Translate raw syscall return to POSIX convention.
+ * Negative values become errno + return -1.
+ */
+static inline int __rlimit_ret(long r)
+{
+    if (r < 0) {
+        errno = (int)(-r);
+        return -1;
+    }
+    return 0;
+}
 
 int getrlimit(int resource, struct rlimit *rlp)
 {
@@ -20,34 +35,45 @@ int getrlimit(int resource, struct rlimit *rlp)
         errno = EINVAL;
         return -1;
     }
-
-    (void)resource;
-
-    /* Return unlimited for all resources. */
-    rlp->rlim_cur = RLIM_INFINITY;
-    rlp->rlim_max = RLIM_INFINITY;
-    return 0;
+    return __rlimit_ret(veridian_syscall2(SYS_GETRLIMIT, resource, rlp));
 }
 
 int setrlimit(int resource, const struct rlimit *rlp)
 {
-    (void)resource;
-    (void)rlp;
-
-    /* Accept but ignore — no enforcement. */
-    return 0;
+    if (!rlp) {
+        errno = EINVAL;
+        return -1;
+    }
+    return __rlimit_ret(veridian_syscall2(SYS_SETRLIMIT, resource, rlp));
 }
 
-int getrusage(int who, struct rusage *usage)
+/* Kernel priority = 100 + 2 * nice, so nice 10 and up land in the low class
+ * and negative values in the system class. */
+#define PRIO_KERNEL_NORMAL 100
+
+int getpriority(int which, id_t who)
 {
-    if (!usage) {
-        errno = EINVAL;
+    long r = veridian_syscall2(SYS_PROCESS_GETPRIORITY, which, who);
+    int nice;
+
+    if (r < 0) {
+        errno = (int)(-r);
         return -1;
     }
+    nice = (int)(r - PRIO_KERNEL_NORMAL) / 2;
+    if (nice < PRIO_MIN)
+        nice = PRIO_MIN;
+    if (nice > PRIO_MAX - 1)
+        nice = PRIO_MAX - 1;
+    return nice;
+}
 
-    (void)who;
-
-    /* Zero everything — no real accounting yet. */
-    memset(usage, 0, sizeof(*usage));
-    return 0;
+int setpriority(int which, id_t who, int prio)
+{
+    if (prio < PRIO_MIN)
+        prio = PRIO_MIN;
+    if (prio > PRIO_MAX - 1)
+        prio = PRIO_MAX - 1;
+    return __rlimit_ret(veridian_syscall3(SYS_PROCESS_SETPRIORITY, which, who,
+                                          PRIO_KERNEL_NORMAL + 2 * prio));
 }
@@ -27,6 +27,12 @@
 /* Defined in stdlib.c. */
 extern char **environ;
 
+/* Defined in auxv.c. */
+extern void __libc_init_auxv(char **envp);
+
+/* Defined in stdlib.c. */
+extern void __libc_init_heap_profile(void);
+
 /* Defined in stdio.c. */
 extern FILE *stdin;
 extern FILE *stdout;
@@ -54,6 +60,12 @@ void __libc_init(char **envp)
     /* Store the environment pointer. */
     environ = envp;
 
+    /* The auxiliary vector follows envp; this also resolves the vDSO. */
+    __libc_init_auxv(envp);
+
+    /* MALLOC_PROFILE, before the first allocation. */
+    __libc_init_heap_profile();
+
     /*
      * stdio streams (stdin/stdout/stderr) are statically initialized
      * in stdio.c, so no dynamic setup is needed.  Buffers are lazily
@@ -75,6 +87,7 @@ void __libc_init(char **envp)
  *   sp[argc+1]     = NULL (argv terminator)
  *   sp[argc+2..]   = envp pointers
  *   ...            = NULL (envp terminator)
+ *   ...            = auxv (type, value) pairs, ending with AT_NULL
  */
 void __libc_start_main(long *sp)
 {
@@ -18,6 +18,7 @@
 #include <ctype.h>
 #include <sys/wait.h>
 #include <sys/mman.h>
+#include <veridian/heap_profile.h>
 #include <veridian/syscall.h>
 
 /* ========================================================================= */
@@ -34,6 +35,9 @@
  *
  * This replaces the earlier bump allocator (where free was a no-op)
  * which caused ash shell and other BusyBox applets to OOM instantly.
+ *
+ * With MALLOC_PROFILE set, allocations are also counted for
+ * /proc/<pid>/heap; see <veridian/heap_profile.h>.
  */
 
 /* Alignment: all allocations are aligned to this boundary. */
@@ -64,9 +68,14 @@
 
 typedef struct mmap_header {
     unsigned long   magic;      /* MMAP_MAGIC -- distinguishes from sbrk blocks */
-    size_t          map_size;   /* Total mmap'd length (header + usable bytes) */
+    size_t          map_size;   /* Total mmap'd length (header + usable bytes),
+                                 * page-aligned; the low bits hold the
+                                 * profiler's site tag */
 } mmap_header_t;
 
+#define MMAP_SIZE(h)    ((h)->map_size & ~4095UL)
+#define MMAP_SITE(h)    ((h)->map_size & 4095UL)
+
 /* mmap_header_t is placed immediately before the pointer returned to the
  * caller.  It must be aligned so the usable region starts on an ALLOC_ALIGN
  * boundary. */
@@ -74,13 +83,137 @@ typedef struct mmap_header {
 
 typedef struct block_header {
     size_t                  size;   /* Usable size (excluding header) */
-    struct block_header    *next;   /* Next free block (only valid when free) */
+    struct block_header    *next;   /* Next free block when free; the
+                                     * profiler's site tag when allocated */
 } block_header_t;
 
 #define HEADER_SIZE     ((sizeof(block_header_t) + ALLOC_ALIGN - 1) & ~(ALLOC_ALIGN - 1))
 
 static block_header_t *free_list = NULL;
 
+/* ========================================================================= */
+/* Heap profiling                                                            */
+/* ========================================================================= */
+
+/*
+ * Counters for MALLOC_PROFILE, registered with the kernel so that it can
+ * read them out of this process.  Allocated blocks carry a site tag: the
+ * index + 1 of the call site they were sampled for, 0 if not sampled.
+ */
+static struct veridian_heap_stats heap_stats;
+static unsigned long heap_ticket;
+
+void __libc_init_heap_profile(void)
+{
+    const char *env = getenv("MALLOC_PROFILE");
+    long interval = HEAP_PROFILE_INTERVAL;
+
+    if (!env)
+        return;
+    if (strcmp(env, "counts") == 0) {
+        heap_stats.mode = HEAP_PROFILE_COUNTS;
+    } else if (strncmp(env, "sites", 5) == 0 && (env[5] == '\0' || env[5] == ':')) {
+        heap_stats.mode = HEAP_PROFILE_SITES;
+        if (env[5] == ':')
+            interval = atol(env + 6);
+        if (interval <= 0 || interval > 65536)
+            interval = HEAP_PROFILE_INTERVAL;
+    } else {
+        return;
+    }
+    heap_stats.magic = HEAP_PROFILE_MAGIC;
+    heap_stats.version = HEAP_PROFILE_VERSION;
+    heap_stats.sample_interval = (uint32_t)interval;
+    /* An older kernel without SYS_HEAP_PROFILE just cannot show them. */
+    veridian_syscall2(SYS_HEAP_PROFILE, (long)&heap_stats, (long)sizeof(heap_stats));
+}
+
+const struct veridian_heap_stats *veridian_heap_stats(void)
+{
+    return heap_stats.mode ? &heap_stats : NULL;
+}
+
+static struct veridian_heap_class *size_class(size_t usable)
+{
+    unsigned int class = 0;
+
+    while (class < HEAP_PROFILE_CLASSES - 1 && usable > ((size_t)16 << class))
+        class++;
+    return &heap_stats.classes[class];
+}
+
+static void count_alloc(size_t usable)
+{
+    struct veridian_heap_class *class = size_class(usable);
+
+    class->allocs++;
+    class->bytes += usable;
+    heap_stats.allocs++;
+    heap_stats.live_bytes += usable;
+    if (heap_stats.live_bytes > heap_stats.peak_bytes)
+        heap_stats.peak_bytes = heap_stats.live_bytes;
+}
+
+static void count_free(size_t usable)
+{
+    size_class(usable)->frees++;
+    heap_stats.frees++;
+    heap_stats.live_bytes -= usable;
+}
+
+/*
+ * Account a new block of `usable` bytes allocated for the caller at `pc`,
+ * sampling it if it is due or `always`.  Returns the block's site tag.
+ */
+static unsigned long profile_alloc(size_t usable, void *pc, int always)
+{
+    unsigned long start, i;
+
+    if (!heap_stats.mode)
+        return 0;
+    count_alloc(usable);
+    if (heap_stats.mode != HEAP_PROFILE_SITES ||
+        (heap_ticket++ % heap_stats.sample_interval != 0 && !always))
+        return 0;
+
+    start = ((unsigned long)pc >> 2) % HEAP_PROFILE_SITES_MAX;
+    for (i = 0; i < HEAP_PROFILE_SITES_MAX; i++) {
+        struct veridian_heap_site *site =
+            &heap_stats.sites[(start + i) % HEAP_PROFILE_SITES_MAX];
+
+        if (site->pc == 0)
+            site->pc = (uint64_t)(unsigned long)pc;
+        if (site->pc == (uint64_t)(unsigned long)pc) {
+            site->allocs++;
+            site->bytes += usable;
+            site->live_bytes += usable;
+            return (unsigned long)(site - heap_stats.sites) + 1;
+        }
+    }
+    heap_stats.dropped++;
+    return 0;
+}
+
+static void profile_free(size_t usable, unsigned long tag)
+{
+    if (!heap_stats.mode)
+        return;
+    count_free(usable);
+    if (tag)
+        heap_stats.sites[tag - 1].live_bytes -= usable;
+}
+
+/* A block grew in place from `old_usable` to `new_usable` bytes. */
+static void profile_resize(size_t old_usable, size_t new_usable, unsigned long tag)
+{
+    if (!heap_stats.mode)
+        return;
+    count_free(old_usable);
+    count_alloc(new_usable);
+    if (tag)
+        heap_stats.sites[tag - 1].live_bytes += new_usable - old_usable;
+}
+
 /*
  * Round size up to alignment boundary.
  */
@@ -124,7 +257,8 @@ static void free_insert(block_header_t *blk)
     }
 }
 
-void *malloc(size_t size)
+/* malloc() on behalf of the caller at `pc`, for the profiler. */
+static void *do_malloc(size_t size, void *pc)
 {
     if (size == 0)
         return NULL;
@@ -158,7 +292,7 @@ void *malloc(size_t size)
 
         mmap_header_t *hdr = (mmap_header_t *)mem;
         hdr->magic    = MMAP_MAGIC;
-        hdr->map_size = map_size;
+        hdr->map_size = map_size | profile_alloc(map_size - MMAP_HDR_SIZE, pc, 1);
 
         return (char *)mem + MMAP_HDR_SIZE;
     }
@@ -187,7 +321,7 @@ void *malloc(size_t size)
                 else
                     free_list = cur->next;
             }
-            cur->next = NULL;
+            cur->next = (block_header_t *)profile_alloc(cur->size, pc, 0);
             return (char *)cur + HEADER_SIZE;
         }
         prev = cur;
@@ -220,9 +354,15 @@ void *malloc(size_t size)
         free_insert(rem);
     }
 
+    blk->next = (block_header_t *)profile_alloc(blk->size, pc, 0);
     return (char *)blk + HEADER_SIZE;
 }
 
+void *malloc(size_t size)
+{
+    return do_malloc(size, __builtin_return_address(0));
+}
+
 void free(void *ptr)
 {
     if (!ptr)
@@ -236,7 +376,8 @@ void free(void *ptr)
      */
     mmap_header_t *mhdr = (mmap_header_t *)((char *)ptr - MMAP_HDR_SIZE);
     if (mhdr->magic == MMAP_MAGIC) {
-        size_t map_size = mhdr->map_size;
+        size_t map_size = MMAP_SIZE(mhdr);
+        profile_free(map_size - MMAP_HDR_SIZE, MMAP_SITE(mhdr));
         /* Clear magic before unmap to prevent double-free confusion. */
         mhdr->magic = 0;
         munmap(mhdr, map_size);
@@ -244,6 +385,7 @@ void free(void *ptr)
     }
 
     block_header_t *blk = (block_header_t *)((char *)ptr - HEADER_SIZE);
+    profile_free(blk->size, (unsigned long)blk->next);
     free_insert(blk);
 }
 
@@ -255,7 +397,7 @@ void *calloc(size_t count, size_t size)
         errno = ENOMEM;
         return NULL;
     }
-    void *p = malloc(total);
+    void *p = do_malloc(total, __builtin_return_address(0));
     if (p)
         memset(p, 0, total);
     return p;
@@ -263,8 +405,10 @@ void *calloc(size_t count, size_t size)
 
 void *realloc(void *ptr, size_t size)
 {
+    void *pc = __builtin_return_address(0);
+
     if (!ptr)
-        return malloc(size);
+        return do_malloc(size, pc);
     if (size == 0) {
         free(ptr);
         return NULL;
@@ -281,11 +425,11 @@ void *realloc(void *ptr, size_t size)
      */
     mmap_header_t *mhdr = (mmap_header_t *)((char *)ptr - MMAP_HDR_SIZE);
     if (mhdr->magic == MMAP_MAGIC) {
-        size_t old_usable = mhdr->map_size - MMAP_HDR_SIZE;
+        size_t old_usable = MMAP_SIZE(mhdr) - MMAP_HDR_SIZE;
         if (old_usable >= size)
             return ptr;     /* Existing mmap region is large enough. */
 
-        void *newp = malloc(size);
+        void *newp = do_malloc(size, pc);
         if (!newp)
             return NULL;
         memcpy(newp, ptr, old_usable < size ? old_usable : size);
@@ -312,6 +456,8 @@ void *realloc(void *ptr, size_t size)
         if (fcur == next_addr) {
             size_t combined = blk->size + HEADER_SIZE + fcur->size;
             if (combined >= size) {
+                size_t old_size = blk->size;
+
                 /* Remove fcur from free list. */
                 if (fprev)
                     fprev->next = fcur->next;
@@ -328,6 +474,7 @@ void *realloc(void *ptr, size_t size)
                     blk->size = size;
                     free_insert(rem);
                 }
+                profile_resize(old_size, blk->size, (unsigned long)blk->next);
                 return ptr;     /* Grew in place -- no copy needed. */
             }
             break;
@@ -337,7 +484,7 @@ void *realloc(void *ptr, size_t size)
     }
 
     /* Cannot grow in place.  Allocate, copy, free. */
-    void *newp = malloc(size);
+    void *newp = do_malloc(size, pc);
     if (!newp)
         return NULL;
     memcpy(newp, ptr, blk->size);
@@ -405,32 +405,88 @@ char *strtok(char *str, const char *delim)
 char *strerror(int errnum)
 {
     switch (errnum) {
-    case 0:         return (char *)"Success";
-    case ENOSYS:    return (char *)"Function not implemented";
-    case EINVAL:    return (char *)"Invalid argument";
-    case EPERM:     return (char *)"Operation not permitted";
-    case ENOENT:    return (char *)"No such file or directory";
-    case ENOMEM:    return (char *)"Cannot allocate memory";
-    case EAGAIN:    return (char *)"Resource temporarily unavailable";
-    case EINTR:     return (char *)"Interrupted system call";
-    case EFAULT:    return (char *)"Bad address";
-    case EACCES:    return (char *)"Permission denied";
-    case ESRCH:     return (char *)"No such process";
-    case EEXIST:    return (char *)"File exists";
-    case EBADF:     return (char *)"Bad file descriptor";
-    case EIO:       return (char *)"Input/output error";
-    case ENOEXEC:   return (char *)"Exec format error";
-    case ECHILD:    return (char *)"No child processes";
-    case EBUSY:     return (char *)"Device or resource busy";
-    case ENOTDIR:   return (char *)"Not a directory";
-    case EISDIR:    return (char *)"Is a directory";
-    case EMFILE:    return (char *)"Too many open files";
-    case ENOSPC:    return (char *)"No space left on device";
-    case EPIPE:     return (char *)"Broken pipe";
-    case ERANGE:    return (char *)"Numerical result out of range";
-    case ENOTTY:    return (char *)"Inappropriate ioctl for device";
-    case ENOTEMPTY: return (char *)"Directory not empty";
-    default:        return (char *)"Unknown error";
+    case 0:                return (char *)"Success";
+    case ENOSYS:           return (char *)"Function not implemented";
+    case EINVAL:           return (char *)"Invalid argument";
+    case EPERM:            return (char *)"Operation not permitted";
+    case ENOENT:           return (char *)"No such file or directory";
+    case ENOMEM:           return (char *)"Cannot allocate memory";
+    case EAGAIN:           return (char *)"Resource temporarily unavailable";
+    case EINTR:            return (char *)"Interrupted system call";
+    case EILSEQ:           return (char *)"Invalid state";
+    case EFAULT:           return (char *)"Bad address";
+    case ECAPINVAL:        return (char *)"Invalid capability";
+    case ECAPREVOKED:      return (char *)"Capability revoked";
+    case ECAPRIGHTS:       return (char *)"Insufficient capability rights";
+    case ECAPNOTFOUND:     return (char *)"Capability not found";
+    case ECAPEXISTS:       return (char *)"Capability already exists";
+    case ECAPOBJECT:       return (char *)"Invalid capability object";
+    case ECAPDELEG:        return (char *)"Capability delegation denied";
+    case ENOMAPPING:       return (char *)"Address not mapped";
+    case EACCES:           return (char *)"Permission denied";
+    case ESRCH:            return (char *)"No such process";
+    case EEXIST:           return (char *)"File exists";
+    case EBADF:            return (char *)"Bad file descriptor";
+    case EIO:              return (char *)"Input/output error";
+    case ENXIO:            return (char *)"No such device or address";
+    case E2BIG:            return (char *)"Argument list too long";
+    case ENOEXEC:          return (char *)"Exec format error";
+    case ECHILD:           return (char *)"No child processes";
+    case EBUSY:            return (char *)"Device or resource busy";
+    case ENOTDIR:          return (char *)"Not a directory";
+    case EISDIR:           return (char *)"Is a directory";
+    case EMFILE:           return (char *)"Too many open files";
+    case ENFILE:           return (char *)"Too many open files in system";
+    case ENOTTY:           return (char *)"Inappropriate ioctl for device";
+    case ETXTBSY:          return (char *)"Text file busy";
+    case EFBIG:            return (char *)"File too large";
+    case ENOSPC:           return (char *)"No space left on device";
+    case ESPIPE:           return (char *)"Illegal seek";
+    case EROFS:            return (char *)"Read-only file system";
+    case EMLINK:           return (char *)"Too many links";
+    case EPIPE:            return (char *)"Broken pipe";
+    case EDOM:             return (char *)"Numerical argument out of domain";
+    case ERANGE:           return (char *)"Numerical result out of range";
+    case EDEADLK:          return (char *)"Resource deadlock avoided";
+    case ENAMETOOLONG:     return (char *)"File name too long";
+    case ENOLCK:           return (char *)"No locks available";
+    case ENOTEMPTY:        return (char *)"Directory not empty";
+    case ELOOP:            return (char *)"Too many levels of symbolic links";
+    case ENOMSG:           return (char *)"No message of desired type";
+    case EXDEV:            return (char *)"Invalid cross-device link";
+    case ECONNREFUSED:     return (char *)"Connection refused";
+    case ECONNRESET:       return (char *)"Connection reset by peer";
+    case ENOBUFS:          return (char *)"No buffer space available";
+    case EPROTONOSUPPORT:  return (char *)"Protocol not supported";
+    case ENOTSUP:          return (char *)"Operation not supported";
+    case EADDRINUSE:       return (char *)"Address already in use";
+    case EADDRNOTAVAIL:    return (char *)"Cannot assign requested address";
+    case ENETUNREACH:      return (char *)"Network is unreachable";
+    case ETIMEDOUT:        return (char *)"Connection timed out";
+    case EALREADY:         return (char *)"Operation already in progress";
+    case EINPROGRESS:      return (char *)"Operation now in progress";
+    case ENOTSOCK:         return (char *)"Socket operation on non-socket";
+    case EDESTADDRREQ:     return (char *)"Destination address required";
+    case EMSGSIZE:         return (char *)"Message too long";
+    case EPROTOTYPE:       return (char *)"Protocol wrong type for socket";
+    case ENOTCONN:         return (char *)"Transport endpoint is not connected";
+    case EISCONN:          return (char *)"Transport endpoint is already connected";
+    case EAFNOSUPPORT:     return (char *)"Address family not supported by protocol";
+    case ECONNABORTED:     return (char *)"Software caused connection abort";
+    case EHOSTUNREACH:     return (char *)"No route to host";
+    case ENETDOWN:         return (char *)"Network is down";
+    case ENETRESET:        return (char *)"Network dropped connection on reset";
+    case ENOPROTOOPT:      return (char *)"Protocol not available";
+    case ENODEV:           return (char *)"No such device";
+    case EOVERFLOW:        return (char *)"Value too large for defined data type";
+    case EPROTO:           return (char *)"Protocol error";
+    case ECANCELED:        return (char *)"Operation canceled";
+    case EOWNERDEAD:       return (char *)"Owner died";
+    case ENOTRECOVERABLE:  return (char *)"State not recoverable";
+    case ENOLINK:          return (char *)"Link has been severed";
+    case ERESOURCELIMIT:   return (char *)"Resource limit exceeded";
+    case ENODATA:          return (char *)"No data available";
+    default:               return (char *)"Unknown error";
     }
 }
 
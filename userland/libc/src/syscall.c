@@ -20,6 +20,11 @@
 #include <veridian/fcntl.h>
 #include <veridian/mman.h>
 #include <sys/utsname.h>
+#include <sys/random.h>
+#include <sys/resource.h>
+#include <sys/sendfile.h>
+#include <sys/time.h>
+#include <sys/times.h>
 #include <time.h>
 #include <errno.h>
 #include <stddef.h>
@@ -102,6 +107,19 @@ int pipe(int pipefd[2])
         veridian_syscall1(SYS_FILE_PIPE, pipefd));
 }
 
+/* The kernel takes O_CLOEXEC with our value here, not Linux's. */
+int dup3(int oldfd, int newfd, int flags)
+{
+    return (int)__syscall_ret(
+        veridian_syscall3(SYS_FILE_DUP3, oldfd, newfd, flags));
+}
+
+int pipe2(int pipefd[2], int flags)
+{
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_FILE_PIPE2, pipefd, flags));
+}
+
 int unlink(const char *pathname)
 {
     return (int)__syscall_ret(
@@ -119,15 +137,39 @@ void sync(void)
     veridian_syscall0(SYS_FS_SYNC);
 }
 
+/* Status flags as the kernel encodes them (Linux values). */
+#define __KERNEL_O_ACCMODE  0x0003
+#define __KERNEL_O_NONBLOCK 0x0800
+
 int fcntl(int fd, int cmd, ...)
 {
     long arg = 0;
+    long ret;
     __builtin_va_list ap;
     __builtin_va_start(ap, cmd);
     arg = __builtin_va_arg(ap, long);
     __builtin_va_end(ap);
-    return (int)__syscall_ret(
-        veridian_syscall3(SYS_FILE_FCNTL, fd, cmd, arg));
+
+    /*
+     * The kernel reports and takes file status flags with Linux values;
+     * translate the access mode and O_NONBLOCK to and from ours.
+     */
+    if (cmd == F_SETFL) {
+        long kflags = arg & O_APPEND;
+        if (arg & O_NONBLOCK)
+            kflags |= __KERNEL_O_NONBLOCK;
+        arg = kflags;
+    }
+    ret = veridian_syscall3(SYS_FILE_FCNTL, fd, cmd, arg);
+    if (cmd == F_GETFL && ret >= 0) {
+        long flags = (ret & __KERNEL_O_ACCMODE) + 1; /* 0/1/2 -> O_RDONLY.. */
+        if (ret & O_APPEND)
+            flags |= O_APPEND;
+        if (ret & __KERNEL_O_NONBLOCK)
+            flags |= O_NONBLOCK;
+        ret = flags;
+    }
+    return (int)__syscall_ret(ret);
 }
 
 int rename(const char *oldpath, const char *newpath)
@@ -218,8 +260,17 @@ pid_t getppid(void)
     return (pid_t)veridian_syscall0(SYS_PROCESS_GETPPID);
 }
 
+/* Defined in auxv.c; NULL without a vDSO. */
+extern long (*__vdso_gettid)(void);
+extern long (*__vdso_sched_yield)(void);
+
 pid_t gettid(void)
 {
+    if (__vdso_gettid) {
+        long tid = __vdso_gettid();
+        if (tid != -ENOSYS)
+            return (pid_t)tid;
+    }
     return (pid_t)veridian_syscall0(SYS_THREAD_GETTID);
 }
 
@@ -259,6 +310,9 @@ pid_t wait(int *wstatus)
 
 int sched_yield(void)
 {
+    /* The vDSO only answers when no other task is ready to run. */
+    if (__vdso_sched_yield && __vdso_sched_yield() == 0)
+        return 0;
     return (int)__syscall_ret(
         veridian_syscall0(SYS_PROCESS_YIELD));
 }
@@ -475,6 +529,70 @@ int setgid(gid_t gid)
         veridian_syscall1(SYS_SETGID, gid));
 }
 
+int setreuid(uid_t ruid, uid_t euid)
+{
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_SETREUID, ruid, euid));
+}
+
+int setregid(gid_t rgid, gid_t egid)
+{
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_SETREGID, rgid, egid));
+}
+
+int seteuid(uid_t euid)
+{
+    return setreuid((uid_t)-1, euid);
+}
+
+int setegid(gid_t egid)
+{
+    return setregid((gid_t)-1, egid);
+}
+
+int getresuid(uid_t *ruid, uid_t *euid, uid_t *suid)
+{
+    return (int)__syscall_ret(
+        veridian_syscall3(SYS_GETRESUID, ruid, euid, suid));
+}
+
+int getresgid(gid_t *rgid, gid_t *egid, gid_t *sgid)
+{
+    return (int)__syscall_ret(
+        veridian_syscall3(SYS_GETRESGID, rgid, egid, sgid));
+}
+
+int getgroups(int size, gid_t list[])
+{
+    if (size < 0) {
+        errno = EINVAL;
+        return -1;
+    }
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_GETGROUPS, size, list));
+}
+
+int setgroups(size_t size, const gid_t *list)
+{
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_SETGROUPS, size, list));
+}
+
+/* ========================================================================= */
+/* Password hashing                                                          */
+/* ========================================================================= */
+
+char *crypt(const char *key, const char *salt)
+{
+    static char hash[160];
+
+    if (__syscall_ret(veridian_syscall4(SYS_CRYPT, key, salt,
+                                        hash, sizeof(hash))) < 0)
+        return NULL;
+    return hash;
+}
+
 /* ========================================================================= */
 /* Process groups and sessions                                               */
 /* ========================================================================= */
@@ -575,6 +693,40 @@ ssize_t pwrite(int fd, const void *buf, size_t count, off_t offset)
         veridian_syscall4(SYS_FILE_PWRITE, fd, buf, count, offset));
 }
 
+/* ========================================================================= */
+/* In-kernel transfer: sendfile, splice                                      */
+/* ========================================================================= */
+
+ssize_t sendfile(int out_fd, int in_fd, off_t *offset, size_t count)
+{
+    return (ssize_t)__syscall_ret(
+        veridian_syscall4(SYS_SENDFILE, out_fd, in_fd, offset, count));
+}
+
+ssize_t splice(int fd_in, off_t *off_in, int fd_out, off_t *off_out,
+               size_t len, unsigned int flags)
+{
+    (void)flags;  /* Hints only; the kernel takes five arguments. */
+    return (ssize_t)__syscall_ret(
+        veridian_syscall5(SYS_SPLICE, fd_in, off_in, fd_out, off_out, len));
+}
+
+/* ========================================================================= */
+/* Space management: fallocate, posix_fallocate                              */
+/* ========================================================================= */
+
+int fallocate(int fd, int mode, off_t offset, off_t len)
+{
+    return (int)__syscall_ret(
+        veridian_syscall4(SYS_FALLOCATE, fd, mode, offset, len));
+}
+
+int posix_fallocate(int fd, off_t offset, off_t len)
+{
+    long r = veridian_syscall4(SYS_FALLOCATE, fd, 0, offset, len);
+    return r < 0 ? (int)-r : 0;
+}
+
 /* ========================================================================= */
 /* *at() family: openat, fstatat, unlinkat, mkdirat, renameat               */
 /* ========================================================================= */
@@ -711,3 +863,41 @@ int uname(struct utsname *buf)
     return (int)__syscall_ret(
         veridian_syscall1(SYS_PROCESS_UNAME, buf));
 }
+
+/* ========================================================================= */
+/* Randomness                                                                */
+/* ========================================================================= */
+
+ssize_t getrandom(void *buf, size_t buflen, unsigned int flags)
+{
+    return (ssize_t)__syscall_ret(
+        veridian_syscall3(SYS_GETRANDOM, buf, buflen, flags));
+}
+
+/* ========================================================================= */
+/* Interval timers and resource usage                                        */
+/* ========================================================================= */
+
+int getitimer(int which, struct itimerval *curr_value)
+{
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_GETITIMER, which, curr_value));
+}
+
+int setitimer(int which, const struct itimerval *new_value,
+              struct itimerval *old_value)
+{
+    return (int)__syscall_ret(
+        veridian_syscall3(SYS_SETITIMER, which, new_value, old_value));
+}
+
+int getrusage(int who, struct rusage *usage)
+{
+    return (int)__syscall_ret(
+        veridian_syscall2(SYS_GETRUSAGE, who, usage));
+}
+
+clock_t times(struct tms *buf)
+{
+    return (clock_t)__syscall_ret(veridian_syscall1(SYS_TIMES, buf));
+}
@@ -16,9 +16,16 @@
 /* clock_gettime                                                             */
 /* ========================================================================= */
 
+/* Defined in auxv.c; NULL without a vDSO. */
+extern long (*__vdso_clock_gettime)(int, void *);
+
 int clock_gettime(clockid_t clk_id, struct timespec *tp)
 {
-    long ret = veridian_syscall2(SYS_CLOCK_GETTIME, clk_id, tp);
+    long ret = -ENOSYS;
+    if (__vdso_clock_gettime)
+        ret = __vdso_clock_gettime((int)clk_id, tp);
+    if (ret == -ENOSYS)
+        ret = veridian_syscall2(SYS_CLOCK_GETTIME, clk_id, tp);
     if (ret < 0) {
         errno = (int)(-ret);
         return -1;
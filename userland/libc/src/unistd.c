@@ -16,6 +16,7 @@
 #include <time.h>
 #include <stdarg.h>
 #include <sys/ioctl.h>
+#include <sys/random.h>
 
 /* ========================================================================= */
 /* Sleep                                                                     */
@@ -41,6 +42,32 @@ int usleep(unsigned int usec)
     return nanosleep(&req, NULL);
 }
 
+/* ========================================================================= */
+/* getentropy                                                                */
+/* ========================================================================= */
+
+int getentropy(void *buf, size_t length)
+{
+    unsigned char *p = buf;
+
+    if (length > 256) {
+        errno = EIO;
+        return -1;
+    }
+
+    while (length > 0) {
+        ssize_t n = getrandom(p, length, 0);
+        if (n < 0) {
+            if (errno == EINTR)
+                continue;
+            return -1;
+        }
+        p += n;
+        length -= (size_t)n;
+    }
+    return 0;
+}
+
 /* ========================================================================= */
 /* exec family                                                               */
 /* ========================================================================= */
@@ -43,9 +43,15 @@
 #define MAP_ANON    0x20
 
 /* ===================================================================
- * Raw syscall wrappers (x86_64 SYSCALL convention)
+ * Raw syscall wrappers
+ *
+ *   x86_64:  syscall -- nr in rax, args in rdi/rsi/rdx/r10/r8/r9
+ *   aarch64: svc #0  -- nr in x8,  args in x0-x5
+ *   riscv64: ecall   -- nr in a7,  args in a0-a5
  * =================================================================== */
 
+#if defined(__x86_64__)
+
 static long syscall0(long nr)
 {
     long ret;
@@ -79,10 +85,9 @@ static long syscall2(long nr, long a1, long a2)
 static long syscall3(long nr, long a1, long a2, long a3)
 {
     long ret;
-    register long r10 __asm__("r10") = a3;
     __asm__ volatile("syscall"
         : "=a"(ret)
-        : "a"(nr), "D"(a1), "S"(a2), "r"(r10)
+        : "a"(nr), "D"(a1), "S"(a2), "d"(a3)
         : "rcx", "r11", "memory");
     return ret;
 }
@@ -90,11 +95,10 @@ static long syscall3(long nr, long a1, long a2, long a3)
 static long syscall4(long nr, long a1, long a2, long a3, long a4)
 {
     long ret;
-    register long r10 __asm__("r10") = a3;
-    register long r8 __asm__("r8") = a4;
+    register long r10 __asm__("r10") = a4;
     __asm__ volatile("syscall"
         : "=a"(ret)
-        : "a"(nr), "D"(a1), "S"(a2), "r"(r10), "r"(r8)
+        : "a"(nr), "D"(a1), "S"(a2), "d"(a3), "r"(r10)
         : "rcx", "r11", "memory");
     return ret;
 }
@@ -103,17 +107,180 @@ static long syscall6(long nr, long a1, long a2, long a3,
                      long a4, long a5, long a6)
 {
     long ret;
-    register long r10 __asm__("r10") = a3;
-    register long r8 __asm__("r8") = a4;
-    register long r9 __asm__("r9") = a5;
-    (void)a6; /* sixth arg via stack on some conventions; unused here */
+    register long r10 __asm__("r10") = a4;
+    register long r8 __asm__("r8") = a5;
+    register long r9 __asm__("r9") = a6;
     __asm__ volatile("syscall"
         : "=a"(ret)
-        : "a"(nr), "D"(a1), "S"(a2), "r"(r10), "r"(r8), "r"(r9)
+        : "a"(nr), "D"(a1), "S"(a2), "d"(a3), "r"(r10), "r"(r8), "r"(r9)
         : "rcx", "r11", "memory");
     return ret;
 }
 
+#elif defined(__aarch64__)
+
+static long syscall0(long nr)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0");
+    __asm__ volatile("svc #0"
+        : "=r"(x0)
+        : "r"(x8)
+        : "memory");
+    return x0;
+}
+
+static long syscall1(long nr, long a1)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8)
+        : "memory");
+    return x0;
+}
+
+static long syscall2(long nr, long a1, long a2)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1)
+        : "memory");
+    return x0;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2)
+        : "memory");
+    return x0;
+}
+
+static long syscall4(long nr, long a1, long a2, long a3, long a4)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    register long x3 __asm__("x3") = a4;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2), "r"(x3)
+        : "memory");
+    return x0;
+}
+
+static long syscall6(long nr, long a1, long a2, long a3,
+                     long a4, long a5, long a6)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    register long x3 __asm__("x3") = a4;
+    register long x4 __asm__("x4") = a5;
+    register long x5 __asm__("x5") = a6;
+    __asm__ volatile("svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2), "r"(x3), "r"(x4), "r"(x5)
+        : "memory");
+    return x0;
+}
+
+#elif defined(__riscv) && __riscv_xlen == 64
+
+static long syscall0(long nr)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0");
+    __asm__ volatile("ecall"
+        : "=r"(a0)
+        : "r"(a7)
+        : "memory");
+    return a0;
+}
+
+static long syscall1(long nr, long a1)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7)
+        : "memory");
+    return a0;
+}
+
+static long syscall2(long nr, long a1, long a2)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1)
+        : "memory");
+    return a0;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2)
+        : "memory");
+    return a0;
+}
+
+static long syscall4(long nr, long a1, long a2, long a3, long a4)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    register long ra3 __asm__("a3") = a4;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2), "r"(ra3)
+        : "memory");
+    return a0;
+}
+
+static long syscall6(long nr, long a1, long a2, long a3,
+                     long a4, long a5, long a6)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    register long ra3 __asm__("a3") = a4;
+    register long ra4 __asm__("a4") = a5;
+    register long ra5 __asm__("a5") = a6;
+    __asm__ volatile("ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2), "r"(ra3), "r"(ra4), "r"(ra5)
+        : "memory");
+    return a0;
+}
+
+#else
+#error "Unsupported architecture for libwayland-client"
+#endif
+
 /* ===================================================================
  * String utilities (no libc)
  * =================================================================== */
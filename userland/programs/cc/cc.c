@@ -0,0 +1,46 @@
+/*
+ * VeridianOS C Compiler Driver -- cc.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * `cc` front end for the on-target Tiny C Compiler (ports/tcc). tcc is
+ * its own preprocessor, assembler and linker, so the driver only has to
+ * force static linking: the C library ships as libc.a alone, and a
+ * dynamically linked a.out would name an interpreter and a libc.so that
+ * the rootfs does not contain.
+ *
+ *   cc hello.c && ./a.out
+ *
+ * All other arguments are passed to tcc unchanged.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+
+#define TCC_PATH "/usr/bin/tcc"
+
+int main(int argc, char **argv)
+{
+    char **args = malloc(((size_t)argc + 2) * sizeof(char *));
+    int n = 0;
+
+    if (!args) {
+        fprintf(stderr, "cc: out of memory\n");
+        return 1;
+    }
+
+    args[n++] = TCC_PATH;
+    /* Harmless with -c/-E/-S, where nothing is linked */
+    args[n++] = "-static";
+    for (int i = 1; i < argc; i++)
+        args[n++] = argv[i];
+    args[n] = NULL;
+
+    execv(TCC_PATH, args);
+    fprintf(stderr, "cc: cannot execute %s: %s\n", TCC_PATH, strerror(errno));
+    return 127;
+}
@@ -0,0 +1,237 @@
+/*
+ * crashdump -- show the kernel crash dump of the previous boot
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * When the kernel panics it saves a crash dump (panic message, registers,
+ * symbolized backtrace and the last kernel log lines) to the
+ * `veridian-crash` disk partition. The next boot keeps it available
+ * through SYS_CRASH_DUMP; this tool prints it, or saves the raw dump
+ * (format: kernel/src/crash/dump.rs) for later.
+ *
+ * Usage:
+ *   crashdump [-o file] [-c]
+ *   crashdump -f file
+ *
+ *   -o file   also write the raw dump to file
+ *   -c        mark the dump as seen, so boot stops warning about it
+ *   -f file   print a raw dump saved earlier instead
+ *
+ * Reading the kernel's copy needs root. Exits 1 if there is no dump.
+ */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/syscall.h>
+
+#define DUMP_MAGIC      "VCRD"
+#define DUMP_VERSION    1
+#define HEADER_LEN      64
+#define MAX_DUMP        (64 * 1024)
+
+#define FLAG_SEEN       1
+
+#define SECTION_MESSAGE   1
+#define SECTION_REGISTERS 2
+#define SECTION_BACKTRACE 3
+#define SECTION_LOG       4
+
+/* SyscallError::ResourceNotFound */
+#define ERR_NOT_FOUND   (-4)
+
+static const char *arch_names[] = { "unknown", "x86_64", "aarch64", "riscv64" };
+
+static uint16_t get16(const uint8_t *p) { return p[0] | p[1] << 8; }
+static uint32_t get32(const uint8_t *p) { return get16(p) | (uint32_t)get16(p + 2) << 16; }
+static uint64_t get64(const uint8_t *p) { return get32(p) | (uint64_t)get32(p + 4) << 32; }
+
+static uint32_t crc32(const uint8_t *data, size_t len)
+{
+    uint32_t crc = ~0u;
+
+    for (size_t i = 0; i < len; i++) {
+        crc ^= data[i];
+        for (int bit = 0; bit < 8; bit++)
+            crc = (crc >> 1) ^ (0xEDB88320u & -(crc & 1));
+    }
+    return ~crc;
+}
+
+static void usage(void)
+{
+    fprintf(stderr, "usage: crashdump [-o file] [-c]\n"
+                    "       crashdump -f file\n");
+    exit(2);
+}
+
+/* Print the lines of `text`, indented. */
+static void print_text(const uint8_t *text, uint32_t len)
+{
+    uint32_t start = 0;
+
+    for (uint32_t i = 0; i <= len; i++) {
+        if (i == len || text[i] == '\n') {
+            if (i > start)
+                printf("  %.*s\n", (int)(i - start), (const char *)text + start);
+            start = i + 1;
+        }
+    }
+}
+
+static void print_registers(const uint8_t *data, uint32_t len)
+{
+    for (uint32_t off = 0; off + 16 <= len; off += 16) {
+        char name[9] = { 0 };
+        memcpy(name, data + off, 8);
+        printf("  %-8s 0x%016llx\n", name, (unsigned long long)get64(data + off + 8));
+    }
+}
+
+static void print_backtrace(const uint8_t *data, uint32_t len)
+{
+    uint32_t off = 0;
+
+    for (int index = 0; off + 16 <= len; index++) {
+        uint64_t addr = get64(data + off);
+        uint32_t offset = get32(data + off + 8);
+        uint32_t name_len = get32(data + off + 12);
+
+        off += 16;
+        if (off + name_len > len)
+            break;
+        if (name_len)
+            printf("  #%-2d 0x%016llx %.*s+0x%x\n", index, (unsigned long long)addr,
+                   (int)name_len, (const char *)data + off, offset);
+        else
+            printf("  #%-2d 0x%016llx ?\n", index, (unsigned long long)addr);
+        off += name_len;
+    }
+}
+
+static int print_dump(const uint8_t *dump, size_t size)
+{
+    uint32_t payload_len, arch;
+    uint64_t uptime;
+    size_t off;
+
+    if (size < HEADER_LEN || memcmp(dump, DUMP_MAGIC, 4) != 0 ||
+        get16(dump + 4) != DUMP_VERSION) {
+        fprintf(stderr, "crashdump: not a crash dump\n");
+        return 1;
+    }
+    payload_len = get32(dump + 8);
+    if (HEADER_LEN + (size_t)payload_len > size ||
+        crc32(dump + HEADER_LEN, payload_len) != get32(dump + 12)) {
+        fprintf(stderr, "crashdump: dump is truncated or corrupt\n");
+        return 1;
+    }
+    uptime = get64(dump + 16);
+    arch = get32(dump + 28);
+
+    printf("Kernel crash dump: VeridianOS %.32s (%s)\n", (const char *)dump + 32,
+           arch_names[arch < 4 ? arch : 0]);
+    printf("  crashed on CPU %u after %llu.%03llu s%s\n", get32(dump + 24),
+           (unsigned long long)(uptime / 1000), (unsigned long long)(uptime % 1000),
+           get16(dump + 6) & FLAG_SEEN ? " (seen)" : "");
+
+    off = HEADER_LEN;
+    while (off + 8 <= HEADER_LEN + (size_t)payload_len) {
+        uint16_t kind = get16(dump + off);
+        uint32_t len = get32(dump + off + 4);
+        const uint8_t *data = dump + off + 8;
+
+        off += 8;
+        if (off + len > HEADER_LEN + (size_t)payload_len)
+            break;
+        switch (kind) {
+        case SECTION_MESSAGE:
+            printf("\nPanic:\n");
+            print_text(data, len);
+            break;
+        case SECTION_REGISTERS:
+            printf("\nRegisters:\n");
+            print_registers(data, len);
+            break;
+        case SECTION_BACKTRACE:
+            printf("\nBacktrace:\n");
+            print_backtrace(data, len);
+            break;
+        case SECTION_LOG:
+            printf("\nKernel log:\n");
+            print_text(data, len);
+            break;
+        default:
+            break;
+        }
+        off += len;
+    }
+    return 0;
+}
+
+static int write_file(const char *path, const uint8_t *data, size_t len)
+{
+    FILE *f = fopen(path, "wb");
+
+    if (!f || fwrite(data, 1, len, f) != len || fclose(f) != 0) {
+        perror(path);
+        return 1;
+    }
+    return 0;
+}
+
+int main(int argc, char **argv)
+{
+    const char *in = NULL, *out = NULL;
+    int ack = 0, opt;
+    static uint8_t dump[MAX_DUMP];
+    long len;
+
+    while ((opt = getopt(argc, argv, "o:cf:")) != -1) {
+        switch (opt) {
+        case 'o': out = optarg; break;
+        case 'c': ack = 1; break;
+        case 'f': in = optarg; break;
+        default: usage();
+        }
+    }
+    if (optind != argc || (in && (out || ack)))
+        usage();
+
+    if (in) {
+        FILE *f = fopen(in, "rb");
+        if (!f) {
+            perror(in);
+            return 1;
+        }
+        len = (long)fread(dump, 1, sizeof(dump), f);
+        fclose(f);
+        return print_dump(dump, (size_t)len);
+    }
+
+    len = veridian_syscall3(SYS_CRASH_DUMP, (long)dump, sizeof(dump), 0);
+    if (len == ERR_NOT_FOUND) {
+        fprintf(stderr, "crashdump: no crash dump\n");
+        return 1;
+    }
+    if (len < 0) {
+        fprintf(stderr, "crashdump: crash_dump failed (%ld)%s\n", len,
+                getuid() != 0 ? ": must be root" : "");
+        return 1;
+    }
+    if (out && write_file(out, dump, (size_t)len) != 0)
+        return 1;
+    if (print_dump(dump, (size_t)len) != 0)
+        return 1;
+    if (ack) {
+        long r = veridian_syscall3(SYS_CRASH_DUMP, 0, 0, CRASH_DUMP_ACK);
+        if (r < 0) {
+            fprintf(stderr, "crashdump: cannot mark the dump as seen (%ld)\n", r);
+            return 1;
+        }
+    }
+    return 0;
+}
@@ -0,0 +1,287 @@
+/*
+ * ip -- VeridianOS network interface configuration
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A small subset of iproute2's `ip`, built on the SIOC*IF* ioctls of an
+ * AF_INET socket. Each interface holds at most one IPv4 address.
+ *
+ * Usage:
+ *   ip link [show [dev]]
+ *   ip link set <dev> up|down
+ *   ip link set <dev> mtu <n>
+ *   ip addr [show [dev]]
+ *   ip addr add <a.b.c.d>/<len> dev <dev>
+ *   ip addr del <a.b.c.d>[/<len>] dev <dev>
+ *
+ * Showing works for everyone; changes need root.
+ */
+
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+
+/* Interface indices are allocated sequentially and never reused. */
+#define MAX_INDEX 64
+
+#define ARPHRD_LOOPBACK 772
+
+static int sock = -1;
+
+static void usage(void)
+{
+    fprintf(stderr,
+            "usage: ip link [show [dev]]\n"
+            "       ip link set <dev> up|down|mtu <n>\n"
+            "       ip addr [show [dev]]\n"
+            "       ip addr add|del <a.b.c.d>/<len> dev <dev>\n");
+    exit(2);
+}
+
+static int if_ioctl(unsigned long request, const char *dev, struct ifreq *ifr)
+{
+    strncpy(ifr->ifr_name, dev, IFNAMSIZ - 1);
+    ifr->ifr_name[IFNAMSIZ - 1] = '\0';
+    return ioctl(sock, request, ifr);
+}
+
+static void die(const char *what, const char *dev)
+{
+    fprintf(stderr, "ip: %s %s: %s\n", what, dev, strerror(errno));
+    exit(1);
+}
+
+/* Parse "a.b.c.d" into network byte order; returns 0 on success. */
+static int parse_ipv4(const char *s, uint32_t *out)
+{
+    unsigned char b[4];
+    for (int i = 0; i < 4; i++) {
+        char *end;
+        unsigned long v = strtoul(s, &end, 10);
+        if (end == s || v > 255 || (i < 3 && *end != '.'))
+            return -1;
+        b[i] = (unsigned char)v;
+        s = end + (i < 3);
+        if (i == 3 && *end != '\0')
+            return -1;
+    }
+    memcpy(out, b, 4);
+    return 0;
+}
+
+static int prefix_len(const unsigned char *mask)
+{
+    int len = 0;
+    for (int i = 0; i < 4; i++)
+        for (int bit = 7; bit >= 0 && (mask[i] >> bit) & 1; bit--)
+            len++;
+    return len;
+}
+
+static void print_flags(short flags)
+{
+    static const struct { short bit; const char *name; } names[] = {
+        { IFF_LOOPBACK,  "LOOPBACK" },
+        { IFF_BROADCAST, "BROADCAST" },
+        { IFF_MULTICAST, "MULTICAST" },
+        { IFF_UP,        "UP" },
+        { IFF_RUNNING,   "LOWER_UP" },
+    };
+    const char *sep = "";
+
+    printf("<");
+    for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+        if (flags & names[i].bit) {
+            printf("%s%s", sep, names[i].name);
+            sep = ",";
+        }
+    }
+    printf(">");
+}
+
+static void show_one(const char *dev, int index, int with_addr)
+{
+    struct ifreq ifr;
+    short flags;
+    int mtu;
+
+    memset(&ifr, 0, sizeof(ifr));
+    if (if_ioctl(SIOCGIFFLAGS, dev, &ifr) < 0)
+        die("cannot query", dev);
+    flags = ifr.ifr_flags;
+    if (if_ioctl(SIOCGIFMTU, dev, &ifr) < 0)
+        die("cannot query", dev);
+    mtu = ifr.ifr_mtu;
+
+    printf("%d: %s: ", index, dev);
+    print_flags(flags);
+    printf(" mtu %d state %s\n", mtu,
+           !(flags & IFF_UP) ? "DOWN" : (flags & IFF_RUNNING) ? "UP" : "UNKNOWN");
+
+    if (if_ioctl(SIOCGIFHWADDR, dev, &ifr) == 0) {
+        const unsigned char *mac = (const unsigned char *)ifr.ifr_hwaddr.sa_data;
+        printf("    link/%s %02x:%02x:%02x:%02x:%02x:%02x\n",
+               ifr.ifr_hwaddr.sa_family == ARPHRD_LOOPBACK ? "loopback" : "ether",
+               mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]);
+    }
+
+    if (!with_addr)
+        return;
+    if (if_ioctl(SIOCGIFADDR, dev, &ifr) < 0)
+        return;
+    struct sockaddr_in *sin = (struct sockaddr_in *)&ifr.ifr_addr;
+    const unsigned char *a = (const unsigned char *)&sin->sin_addr;
+    if (!a[0] && !a[1] && !a[2] && !a[3])
+        return;
+    unsigned char addr[4];
+    memcpy(addr, a, 4);
+    if (if_ioctl(SIOCGIFNETMASK, dev, &ifr) < 0)
+        die("cannot query", dev);
+    printf("    inet %u.%u.%u.%u/%d\n", addr[0], addr[1], addr[2], addr[3],
+           prefix_len((const unsigned char *)&sin->sin_addr));
+}
+
+static int show(const char *only, int with_addr)
+{
+    struct ifreq ifr;
+    int found = 0;
+
+    for (int index = 1; index <= MAX_INDEX; index++) {
+        memset(&ifr, 0, sizeof(ifr));
+        ifr.ifr_ifindex = index;
+        if (ioctl(sock, SIOCGIFNAME, &ifr) < 0)
+            continue;
+        if (only && strcmp(only, ifr.ifr_name) != 0)
+            continue;
+        show_one(ifr.ifr_name, index, with_addr);
+        found = 1;
+    }
+    if (only && !found) {
+        fprintf(stderr, "ip: device \"%s\" does not exist\n", only);
+        return 1;
+    }
+    return 0;
+}
+
+static int link_set(int argc, char **argv)
+{
+    struct ifreq ifr;
+    const char *dev;
+
+    if (argc < 2)
+        usage();
+    dev = argv[0];
+    for (int i = 1; i < argc; i++) {
+        memset(&ifr, 0, sizeof(ifr));
+        if (!strcmp(argv[i], "up") || !strcmp(argv[i], "down")) {
+            if (if_ioctl(SIOCGIFFLAGS, dev, &ifr) < 0)
+                die("cannot query", dev);
+            if (argv[i][0] == 'u')
+                ifr.ifr_flags |= IFF_UP;
+            else
+                ifr.ifr_flags &= ~IFF_UP;
+            if (if_ioctl(SIOCSIFFLAGS, dev, &ifr) < 0)
+                die("cannot set state of", dev);
+        } else if (!strcmp(argv[i], "mtu") && i + 1 < argc) {
+            ifr.ifr_mtu = atoi(argv[++i]);
+            if (if_ioctl(SIOCSIFMTU, dev, &ifr) < 0)
+                die("cannot set mtu of", dev);
+        } else {
+            usage();
+        }
+    }
+    return 0;
+}
+
+static int addr_change(int add, int argc, char **argv)
+{
+    struct ifreq ifr;
+    struct sockaddr_in *sin = (struct sockaddr_in *)&ifr.ifr_addr;
+    uint32_t addr, mask = 0;
+    int len = 32;
+    char *slash;
+
+    if (argc != 3 || strcmp(argv[1], "dev") != 0)
+        usage();
+    slash = strchr(argv[0], '/');
+    if (slash) {
+        *slash = '\0';
+        len = atoi(slash + 1);
+    }
+    if (parse_ipv4(argv[0], &addr) < 0 || len < 0 || len > 32) {
+        fprintf(stderr, "ip: invalid address \"%s\"\n", argv[0]);
+        return 1;
+    }
+
+    if (!add) {
+        memset(&ifr, 0, sizeof(ifr));
+        if (if_ioctl(SIOCGIFADDR, argv[2], &ifr) < 0)
+            die("cannot query", argv[2]);
+        if (memcmp(&sin->sin_addr, &addr, 4) != 0) {
+            fprintf(stderr, "ip: address not assigned to %s\n", argv[2]);
+            return 1;
+        }
+        addr = 0;
+    }
+
+    memset(&ifr, 0, sizeof(ifr));
+    sin->sin_family = AF_INET;
+    memcpy(&sin->sin_addr, &addr, 4);
+    if (if_ioctl(SIOCSIFADDR, argv[2], &ifr) < 0)
+        die("cannot set address of", argv[2]);
+    if (!add)
+        return 0;
+
+    for (int i = 0; i < len; i++)
+        ((unsigned char *)&mask)[i / 8] |= (unsigned char)(0x80 >> (i % 8));
+    memset(&ifr, 0, sizeof(ifr));
+    sin->sin_family = AF_INET;
+    memcpy(&sin->sin_addr, &mask, 4);
+    if (if_ioctl(SIOCSIFNETMASK, argv[2], &ifr) < 0)
+        die("cannot set netmask of", argv[2]);
+    return 0;
+}
+
+int main(int argc, char **argv)
+{
+    const char *obj;
+
+    if (argc < 2)
+        usage();
+    sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        perror("ip: socket");
+        return 1;
+    }
+
+    obj = argv[1];
+    argc -= 2;
+    argv += 2;
+
+    if (!strcmp(obj, "link") || !strcmp(obj, "l")) {
+        if (argc == 0)
+            return show(NULL, 0);
+        if (!strcmp(argv[0], "show"))
+            return show(argc > 1 ? argv[1] : NULL, 0);
+        if (!strcmp(argv[0], "set"))
+            return link_set(argc - 1, argv + 1);
+    } else if (!strcmp(obj, "addr") || !strcmp(obj, "address") || !strcmp(obj, "a")) {
+        if (argc == 0)
+            return show(NULL, 1);
+        if (!strcmp(argv[0], "show"))
+            return show(argc > 1 ? argv[1] : NULL, 1);
+        if (!strcmp(argv[0], "add"))
+            return addr_change(1, argc - 1, argv + 1);
+        if (!strcmp(argv[0], "del"))
+            return addr_change(0, argc - 1, argv + 1);
+    }
+    usage();
+    return 2;
+}
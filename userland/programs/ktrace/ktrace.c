@@ -0,0 +1,362 @@
+/*
+ * ktrace -- VeridianOS kernel trace control and export
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Front end for the kernel tracepoints (SYS_TRACE_CTL). Traces are written
+ * in the Chrome trace-event JSON format, so they open directly in
+ * chrome://tracing or https://ui.perfetto.dev:
+ *
+ *   syscalls and page faults   duration slices (B/E) on the thread
+ *   context switches, IPC,     instant events
+ *   frame alloc/free
+ *
+ * Usage:
+ *   ktrace start [-e event,...] [-p pid]
+ *   ktrace stop
+ *   ktrace clear
+ *   ktrace stat
+ *   ktrace dump [-o file.json]
+ *   ktrace record [-e event,...] [-o file.json] command [args...]
+ *
+ * `record` clears the buffers, traces only the command's process while it
+ * runs, and writes the trace when it exits. All subcommands need root.
+ */
+
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/wait.h>
+#include <veridian/syscall.h>
+
+/* Record layout returned by TRACE_READ (kernel perf::trace::TraceEvent). */
+struct trace_record {
+    uint64_t ts_ns;
+    uint8_t  type;
+    uint8_t  cpu;
+    uint16_t pad;
+    uint32_t tid;
+    uint64_t data[2];
+};
+
+/* Layout returned by TRACE_STATS (kernel perf::trace::TraceStats). */
+struct trace_stats {
+    uint32_t enabled;
+    uint32_t event_mask;
+    uint64_t pid_filter;
+    uint64_t recorded;
+    uint64_t overwritten;
+    uint64_t per_type[TRACE_EV_COUNT];
+};
+
+/* Enough for every per-CPU ring (16 CPUs x 4096 events). */
+#define MAX_RECORDS (16 * 4096)
+
+/* Threads whose process is known from sched_switch events. */
+#define MAX_THREADS 512
+
+static const char *event_names[TRACE_EV_COUNT] = {
+    "syscall_entry", "syscall_exit", "sched_switch_out", "sched_switch_in",
+    "ipc_fast_send", "ipc_fast_recv", "frame_alloc", "frame_free",
+    "page_fault", "ipc_slow_path", "page_fault_done",
+};
+
+static const char *fault_outcomes[] = { "demand", "cow", "stack", "segv" };
+
+static struct {
+    uint32_t tid;
+    uint64_t pid;
+} threads[MAX_THREADS];
+static int nthreads;
+
+static long trace_ctl(long op, long a1, long a2)
+{
+    long r = veridian_syscall3(SYS_TRACE_CTL, op, a1, a2);
+    if (r < 0) {
+        fprintf(stderr, "ktrace: trace_ctl(%ld) failed (%ld)%s\n", op, r,
+                getuid() != 0 ? ": must be root" : "");
+        exit(1);
+    }
+    return r;
+}
+
+static void usage(void)
+{
+    fprintf(stderr,
+            "usage: ktrace start [-e event,...] [-p pid]\n"
+            "       ktrace stop | clear | stat\n"
+            "       ktrace dump [-o file.json]\n"
+            "       ktrace record [-e event,...] [-o file.json] command [args...]\n");
+    exit(2);
+}
+
+/* Parse a comma-separated event list into a mask. */
+static long parse_events(const char *list)
+{
+    char buf[256];
+    long mask = 0;
+
+    snprintf(buf, sizeof(buf), "%s", list);
+    for (char *name = strtok(buf, ","); name; name = strtok(NULL, ",")) {
+        int found = 0;
+        if (strcmp(name, "all") == 0) {
+            mask = (1L << TRACE_EV_COUNT) - 1;
+            continue;
+        }
+        /* "syscall", "sched", "ipc", "frame" and "page_fault" select groups */
+        for (int i = 0; i < TRACE_EV_COUNT; i++) {
+            if (strncmp(event_names[i], name, strlen(name)) == 0) {
+                mask |= 1L << i;
+                found = 1;
+            }
+        }
+        if (!found) {
+            fprintf(stderr, "ktrace: unknown event '%s'\n", name);
+            exit(2);
+        }
+    }
+    return mask;
+}
+
+/* ========================================================================= */
+/* Chrome trace-event JSON                                                   */
+/* ========================================================================= */
+
+static void note_thread(uint32_t tid, uint64_t pid)
+{
+    for (int i = 0; i < nthreads; i++) {
+        if (threads[i].tid == tid) {
+            threads[i].pid = pid;
+            return;
+        }
+    }
+    if (nthreads < MAX_THREADS) {
+        threads[nthreads].tid = tid;
+        threads[nthreads].pid = pid;
+        nthreads++;
+    }
+}
+
+static uint64_t thread_pid(uint32_t tid)
+{
+    for (int i = 0; i < nthreads; i++)
+        if (threads[i].tid == tid)
+            return threads[i].pid;
+    return 0;
+}
+
+static void write_event(FILE *out, const struct trace_record *r)
+{
+    const char *name = event_names[r->type];
+    const char *ph = "i";
+    char nbuf[32];
+    uint64_t pid;
+
+    if (r->type == TRACE_EV_SWITCH_IN || r->type == TRACE_EV_SWITCH_OUT)
+        note_thread((uint32_t)r->data[1], r->data[0]);
+    pid = thread_pid(r->tid);
+
+    switch (r->type) {
+    case TRACE_EV_SYSCALL_ENTRY:
+    case TRACE_EV_SYSCALL_EXIT:
+        snprintf(nbuf, sizeof(nbuf), "sys_%llu", (unsigned long long)r->data[0]);
+        name = nbuf;
+        ph = r->type == TRACE_EV_SYSCALL_ENTRY ? "B" : "E";
+        break;
+    case TRACE_EV_PAGE_FAULT:
+    case TRACE_EV_PAGE_FAULT_DONE:
+        name = "page_fault";
+        ph = r->type == TRACE_EV_PAGE_FAULT ? "B" : "E";
+        break;
+    }
+
+    fprintf(out, ",\n{\"name\":\"%s\",\"cat\":\"%s\",\"ph\":\"%s\","
+            "\"ts\":%llu.%03llu,\"pid\":%llu,\"tid\":%u,",
+            name, event_names[r->type], ph,
+            (unsigned long long)(r->ts_ns / 1000),
+            (unsigned long long)(r->ts_ns % 1000),
+            (unsigned long long)pid, r->tid);
+    if (ph[0] == 'i')
+        fprintf(out, "\"s\":\"t\",");
+
+    switch (r->type) {
+    case TRACE_EV_SYSCALL_ENTRY:
+        fprintf(out, "\"args\":{\"nr\":%llu,\"arg1\":\"0x%llx\",\"cpu\":%u}}",
+                (unsigned long long)r->data[0], (unsigned long long)r->data[1], r->cpu);
+        break;
+    case TRACE_EV_SYSCALL_EXIT:
+        fprintf(out, "\"args\":{\"ret\":%lld}}", (long long)r->data[1]);
+        break;
+    case TRACE_EV_PAGE_FAULT:
+        fprintf(out, "\"args\":{\"addr\":\"0x%llx\",\"error\":\"0x%llx\",\"cpu\":%u}}",
+                (unsigned long long)r->data[0], (unsigned long long)r->data[1], r->cpu);
+        break;
+    case TRACE_EV_PAGE_FAULT_DONE:
+        fprintf(out, "\"args\":{\"outcome\":\"%s\"}}",
+                r->data[1] < 4 ? fault_outcomes[r->data[1]] : "unknown");
+        break;
+    default:
+        fprintf(out, "\"args\":{\"data0\":\"0x%llx\",\"data1\":\"0x%llx\",\"cpu\":%u}}",
+                (unsigned long long)r->data[0], (unsigned long long)r->data[1], r->cpu);
+        break;
+    }
+}
+
+static int dump(const char *path)
+{
+    struct trace_record *records = malloc(MAX_RECORDS * sizeof(*records));
+    struct trace_stats stats;
+    FILE *out = stdout;
+    long n;
+
+    if (!records) {
+        fprintf(stderr, "ktrace: out of memory\n");
+        return 1;
+    }
+    trace_ctl(TRACE_STATS, (long)&stats, sizeof(stats));
+    n = trace_ctl(TRACE_READ, (long)records, MAX_RECORDS * sizeof(*records));
+
+    if (path && !(out = fopen(path, "w"))) {
+        fprintf(stderr, "ktrace: %s: %s\n", path, strerror(errno));
+        free(records);
+        return 1;
+    }
+
+    fprintf(out, "{\"displayTimeUnit\":\"ns\",\"otherData\":{"
+            "\"recorded\":%llu,\"overwritten\":%llu},\"traceEvents\":[",
+            (unsigned long long)stats.recorded,
+            (unsigned long long)stats.overwritten);
+    fprintf(out, "\n{\"name\":\"process_name\",\"ph\":\"M\",\"pid\":0,"
+            "\"args\":{\"name\":\"kernel\"}}");
+    for (long i = 0; i < n; i++)
+        if (records[i].type < TRACE_EV_COUNT)
+            write_event(out, &records[i]);
+    fprintf(out, "\n]}\n");
+
+    if (out != stdout) {
+        fclose(out);
+        fprintf(stderr, "ktrace: wrote %ld events to %s\n", n, path);
+    }
+    free(records);
+    return 0;
+}
+
+/* ========================================================================= */
+/* Subcommands                                                               */
+/* ========================================================================= */
+
+static int stat_cmd(void)
+{
+    struct trace_stats s;
+
+    trace_ctl(TRACE_STATS, (long)&s, sizeof(s));
+    printf("tracing:     %s\n", s.enabled ? "enabled" : "disabled");
+    printf("event mask:  0x%x\n", s.event_mask);
+    if (s.pid_filter)
+        printf("pid filter:  %llu\n", (unsigned long long)s.pid_filter);
+    else
+        printf("pid filter:  all\n");
+    printf("recorded:    %llu (%llu overwritten)\n",
+           (unsigned long long)s.recorded, (unsigned long long)s.overwritten);
+    for (int i = 0; i < TRACE_EV_COUNT; i++)
+        printf("  %-18s %llu\n", event_names[i], (unsigned long long)s.per_type[i]);
+    return 0;
+}
+
+static int record_cmd(char **argv, long mask, const char *path)
+{
+    int sync[2], status;
+    pid_t child;
+    char go;
+
+    if (pipe(sync) < 0) {
+        perror("ktrace: pipe");
+        return 1;
+    }
+    child = fork();
+    if (child < 0) {
+        perror("ktrace: fork");
+        return 1;
+    }
+    if (child == 0) {
+        /* Wait until tracing is armed for our pid, then run the command. */
+        close(sync[1]);
+        if (read(sync[0], &go, 1) != 1)
+            _exit(127);
+        close(sync[0]);
+        execvp(argv[0], argv);
+        fprintf(stderr, "ktrace: %s: %s\n", argv[0], strerror(errno));
+        _exit(127);
+    }
+
+    close(sync[0]);
+    trace_ctl(TRACE_CLEAR, 0, 0);
+    trace_ctl(TRACE_SET_MASK, mask, 0);
+    trace_ctl(TRACE_SET_PID, child, 0);
+    trace_ctl(TRACE_ENABLE, 0, 0);
+    go = 1;
+    if (write(sync[1], &go, 1) != 1)
+        perror("ktrace: write");
+    close(sync[1]);
+
+    while (waitpid(child, &status, 0) < 0 && errno == EINTR)
+        ;
+    trace_ctl(TRACE_DISABLE, 0, 0);
+    trace_ctl(TRACE_SET_PID, 0, 0);
+
+    if (dump(path) != 0)
+        return 1;
+    return WIFEXITED(status) ? WEXITSTATUS(status) : 1;
+}
+
+int main(int argc, char **argv)
+{
+    const char *cmd, *path = NULL;
+    long mask = (1L << TRACE_EV_COUNT) - 1;
+    long pid = 0;
+    int i;
+
+    if (argc < 2)
+        usage();
+    cmd = argv[1];
+
+    /* Options stop at the first non-option (the command for `record`). */
+    for (i = 2; i < argc && argv[i][0] == '-'; i++) {
+        if (strcmp(argv[i], "-e") == 0 && i + 1 < argc)
+            mask = parse_events(argv[++i]);
+        else if (strcmp(argv[i], "-p") == 0 && i + 1 < argc)
+            pid = strtol(argv[++i], NULL, 10);
+        else if (strcmp(argv[i], "-o") == 0 && i + 1 < argc)
+            path = argv[++i];
+        else
+            usage();
+    }
+
+    if (strcmp(cmd, "start") == 0) {
+        trace_ctl(TRACE_SET_MASK, mask, 0);
+        trace_ctl(TRACE_SET_PID, pid, 0);
+        trace_ctl(TRACE_ENABLE, 0, 0);
+        return 0;
+    }
+    if (strcmp(cmd, "stop") == 0) {
+        trace_ctl(TRACE_DISABLE, 0, 0);
+        return 0;
+    }
+    if (strcmp(cmd, "clear") == 0) {
+        trace_ctl(TRACE_CLEAR, 0, 0);
+        return 0;
+    }
+    if (strcmp(cmd, "stat") == 0)
+        return stat_cmd();
+    if (strcmp(cmd, "dump") == 0)
+        return dump(path);
+    if (strcmp(cmd, "record") == 0 && i < argc)
+        return record_cmd(&argv[i], mask, path);
+
+    usage();
+    return 2;
+}
@@ -0,0 +1,338 @@
+/*
+ * login -- VeridianOS user login
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Prompts for a user name and password, checks the password against
+ * /etc/shadow (or the passwd field of /etc/passwd when it is not "x"),
+ * then switches to the user's credentials and executes their shell as a
+ * login shell.
+ *
+ * Password hashes use the kernel's PBKDF2-HMAC-SHA256 via crypt():
+ *
+ *   $pbkdf2-sha256$<rounds>$<salt>$<hex digest>
+ *
+ * An empty hash field means no password; a hash starting with '!' or '*'
+ * means the account is locked.
+ *
+ * init runs login as the leader of a new session with the console as its
+ * controlling terminal.  When started any other way, login establishes the
+ * session itself.
+ *
+ * Usage: login [username]
+ */
+
+#include <grp.h>
+#include <pwd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <termios.h>
+#include <unistd.h>
+#include <sys/ioctl.h>
+
+#define MAX_ATTEMPTS    3
+#define NAME_LEN        64
+#define PASS_LEN        128
+#define HASH_LEN        160
+#define LINE_LEN        512
+
+/* Variables carried over from the caller's environment */
+static const char *const keep_env[] = {
+    "TERM", "TMPDIR", "COMPILER_PATH", "LIBRARY_PATH", NULL
+};
+
+/* ========================================================================= */
+/* Terminal helpers                                                          */
+/* ========================================================================= */
+
+/*
+ * Read one line from stdin into buf (without the newline).
+ * Returns the length, or -1 on EOF with nothing read.
+ */
+static int read_line(char *buf, int size)
+{
+    int len = 0;
+    char ch;
+
+    for (;;) {
+        ssize_t r = read(0, &ch, 1);
+        if (r <= 0) {
+            if (len == 0)
+                return -1;
+            break;
+        }
+        if (ch == '\n' || ch == '\r')
+            break;
+        if (len < size - 1)
+            buf[len++] = ch;
+    }
+    buf[len] = '\0';
+    return len;
+}
+
+/* Prompt for a password with echo disabled. */
+static int read_password(char *buf, int size)
+{
+    struct termios saved, quiet;
+    int have_tty = (tcgetattr(0, &saved) == 0);
+    int len;
+
+    fputs("Password: ", stdout);
+    fflush(stdout);
+
+    if (have_tty) {
+        quiet = saved;
+        quiet.c_lflag &= ~(tcflag_t)ECHO;
+        tcsetattr(0, TCSAFLUSH, &quiet);
+    }
+    len = read_line(buf, size);
+    if (have_tty)
+        tcsetattr(0, TCSAFLUSH, &saved);
+
+    fputs("\n", stdout);
+    return len;
+}
+
+/* Overwrite a secret so it does not linger in memory. */
+static void wipe(char *buf, size_t len)
+{
+    volatile char *p = buf;
+    while (len--)
+        *p++ = 0;
+}
+
+/* ========================================================================= */
+/* Shadow database                                                           */
+/* ========================================================================= */
+
+/*
+ * Look up the password hash for `name` in /etc/shadow
+ * (name:hash:lastchg:min:max:warn:inactive:expire:).
+ * Returns 0 and fills `hash` on success, -1 if there is no entry.
+ */
+static int shadow_hash(const char *name, char *hash, size_t size)
+{
+    FILE *f = fopen("/etc/shadow", "r");
+    char line[LINE_LEN];
+    size_t name_len = strlen(name);
+    int found = -1;
+
+    if (!f)
+        return -1;
+
+    while (fgets(line, sizeof(line), f)) {
+        if (strncmp(line, name, name_len) != 0 || line[name_len] != ':')
+            continue;
+
+        char *start = line + name_len + 1;
+        char *end = start;
+        while (*end && *end != ':' && *end != '\n')
+            end++;
+        size_t len = (size_t)(end - start);
+        if (len >= size)
+            len = size - 1;
+        memcpy(hash, start, len);
+        hash[len] = '\0';
+        found = 0;
+        break;
+    }
+
+    wipe(line, sizeof(line));
+    fclose(f);
+    return found;
+}
+
+/* Compare two strings without leaking where they differ. */
+static int secure_equal(const char *a, const char *b)
+{
+    size_t la = strlen(a), lb = strlen(b);
+    unsigned char diff = (unsigned char)(la != lb);
+    size_t i;
+
+    for (i = 0; i < la && i < lb; i++)
+        diff |= (unsigned char)(a[i] ^ b[i]);
+    return diff == 0;
+}
+
+/*
+ * Check `password` against the stored hash.  Unknown users are checked
+ * against a dummy hash so the response time does not reveal which names
+ * exist.
+ */
+static int check_password(const char *hash, const char *password)
+{
+    static const char dummy[] = "$pbkdf2-sha256$10000$veridian$";
+    const char *computed;
+
+    if (!hash) {
+        (void)crypt(password, dummy);
+        return 0;
+    }
+    if (hash[0] == '\0')
+        return 1;
+    if (hash[0] == '!' || hash[0] == '*')
+        return 0;
+
+    computed = crypt(password, hash);
+    return computed && secure_equal(computed, hash);
+}
+
+/* ========================================================================= */
+/* Session setup                                                             */
+/* ========================================================================= */
+
+/* Become a session leader with the console as controlling terminal. */
+static void establish_session(void)
+{
+    if (getsid(0) != getpid())
+        setsid();
+    ioctl(0, TIOCSCTTY, 0);
+    tcsetpgrp(0, getpgrp());
+}
+
+static void print_motd(void)
+{
+    FILE *f = fopen("/etc/motd", "r");
+    char line[LINE_LEN];
+
+    if (!f)
+        return;
+    while (fgets(line, sizeof(line), f))
+        fputs(line, stdout);
+    fclose(f);
+}
+
+/* Build "NAME=value" into buf and return it. */
+static char *env_entry(char *buf, size_t size, const char *name,
+                       const char *value)
+{
+    snprintf(buf, size, "%s=%s", name, value);
+    return buf;
+}
+
+/* Drop to the user's credentials and exec their shell; only returns on error. */
+static void start_session(const struct passwd *pw)
+{
+    static char e_home[LINE_LEN], e_shell[LINE_LEN], e_user[NAME_LEN + 8];
+    static char e_logname[NAME_LEN + 8], e_path[LINE_LEN];
+    static char e_kept[4][LINE_LEN];
+    char *envp[16];
+    int n = 0, k;
+    const char *shell = (pw->pw_shell && pw->pw_shell[0]) ? pw->pw_shell : "/bin/sh";
+    const char *home = (pw->pw_dir && pw->pw_dir[0]) ? pw->pw_dir : "/";
+    const char *base;
+    char argv0[NAME_LEN + 2];
+    char *argv[2];
+
+    if (initgroups(pw->pw_name, pw->pw_gid) != 0 ||
+        setgid(pw->pw_gid) != 0 ||
+        setuid(pw->pw_uid) != 0) {
+        fputs("login: cannot set user credentials\n", stderr);
+        return;
+    }
+
+    if (chdir(home) != 0) {
+        fprintf(stderr, "login: no home directory %s, using /\n", home);
+        chdir("/");
+        home = "/";
+    }
+
+    envp[n++] = env_entry(e_home, sizeof(e_home), "HOME", home);
+    envp[n++] = env_entry(e_shell, sizeof(e_shell), "SHELL", shell);
+    envp[n++] = env_entry(e_user, sizeof(e_user), "USER", pw->pw_name);
+    envp[n++] = env_entry(e_logname, sizeof(e_logname), "LOGNAME", pw->pw_name);
+    envp[n++] = env_entry(e_path, sizeof(e_path), "PATH",
+                          pw->pw_uid == 0 ? "/bin:/usr/bin:/sbin:/usr/sbin"
+                                          : "/bin:/usr/bin");
+    for (k = 0; keep_env[k]; k++) {
+        const char *value = getenv(keep_env[k]);
+        if (value)
+            envp[n++] = env_entry(e_kept[k], sizeof(e_kept[k]), keep_env[k], value);
+    }
+    envp[n] = NULL;
+
+    /* Login shells are started with a leading '-' in argv[0] */
+    base = strrchr(shell, '/');
+    base = base ? base + 1 : shell;
+    snprintf(argv0, sizeof(argv0), "-%s", base);
+    argv[0] = argv0;
+    argv[1] = NULL;
+
+    print_motd();
+    execve(shell, argv, envp);
+    fprintf(stderr, "login: cannot execute %s\n", shell);
+}
+
+/* ========================================================================= */
+/* Main                                                                      */
+/* ========================================================================= */
+
+int main(int argc, char **argv)
+{
+    char hostname[64] = "veridian";
+    char name[NAME_LEN];
+    char password[PASS_LEN];
+    char hash[HASH_LEN];
+    int attempt;
+
+    if (getuid() != 0 && geteuid() != 0) {
+        fputs("login: must be run as root\n", stderr);
+        return 1;
+    }
+
+    establish_session();
+    gethostname(hostname, sizeof(hostname));
+
+    for (attempt = 0; attempt < MAX_ATTEMPTS; attempt++) {
+        if (attempt == 0 && argc > 1) {
+            strncpy(name, argv[1], sizeof(name) - 1);
+            name[sizeof(name) - 1] = '\0';
+        } else {
+            printf("%s login: ", hostname);
+            fflush(stdout);
+            if (read_line(name, sizeof(name)) < 0)
+                return 1;
+            if (name[0] == '\0') {
+                attempt--;
+                continue;
+            }
+        }
+
+        struct passwd *pw = getpwnam(name);
+        const char *stored = NULL;
+        if (pw) {
+            if (strcmp(pw->pw_passwd, "x") == 0) {
+                if (shadow_hash(name, hash, sizeof(hash)) == 0)
+                    stored = hash;
+            } else {
+                strncpy(hash, pw->pw_passwd, sizeof(hash) - 1);
+                hash[sizeof(hash) - 1] = '\0';
+                stored = hash;
+            }
+        }
+
+        /* Accounts without a password are not prompted */
+        int ok;
+        if (stored && stored[0] == '\0') {
+            ok = 1;
+        } else {
+            if (read_password(password, sizeof(password)) < 0)
+                return 1;
+            ok = check_password(stored, password);
+            wipe(password, sizeof(password));
+        }
+        wipe(hash, sizeof(hash));
+
+        if (ok && pw) {
+            start_session(pw);
+            return 1;
+        }
+
+        sleep(2);
+        puts("Login incorrect");
+    }
+
+    return 1;
+}
@@ -0,0 +1,211 @@
+/*
+ * notify-send -- post a desktop notification
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Front end for the desktop notification service (SYS_NOTIFY), for scripts
+ * and services.
+ *
+ * Usage:
+ *   notify-send [-u low|normal|critical] [-t ms] [-i icon] [-a app]
+ *               [-A [key=]label]... [-p] [-w] summary [body]
+ *   notify-send -c id
+ *
+ * -t 0 keeps the notification until it is clicked away; without -t the
+ * urgency decides. The icon is an image path or an icon name. Each -A adds
+ * an action button (at most three); an action with key `default` has no
+ * button and is invoked by clicking the notification. -p prints the
+ * notification ID, -w waits until the notification is closed and prints
+ * the key of the action the user picked, if any. -c closes a notification.
+ */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/syscall.h>
+
+#define MSG_MAX     4096
+#define HEADER_SIZE 18
+
+struct action {
+    const char *key;
+    const char *label;
+    size_t key_len;
+};
+
+static unsigned char msg[MSG_MAX];
+static size_t msg_len;
+
+static void usage(void)
+{
+    fprintf(stderr,
+            "usage: notify-send [-u low|normal|critical] [-t ms] [-i icon] [-a app]\n"
+            "                   [-A [key=]label]... [-p] [-w] summary [body]\n"
+            "       notify-send -c id\n");
+    exit(2);
+}
+
+static void put_bytes(const void *data, size_t len)
+{
+    if (msg_len + len > MSG_MAX) {
+        fprintf(stderr, "notify-send: message too long\n");
+        exit(1);
+    }
+    memcpy(msg + msg_len, data, len);
+    msg_len += len;
+}
+
+static void put_le(uint64_t value, int bytes)
+{
+    unsigned char buf[8];
+    int i;
+
+    for (i = 0; i < bytes; i++)
+        buf[i] = (unsigned char)(value >> (8 * i));
+    put_bytes(buf, (size_t)bytes);
+}
+
+/* A string is a 16-bit length followed by the bytes, no terminator. */
+static void put_string(const char *s, size_t len)
+{
+    if (len > 0xFFFF)
+        len = 0xFFFF;
+    put_le(len, 2);
+    put_bytes(s, len);
+}
+
+/* Start a message: type, urgency, ID, timeout, no reply endpoint. */
+static void begin(int type, int urgency, uint32_t id, uint32_t timeout)
+{
+    msg_len = 0;
+    put_le((uint64_t)type, 1);
+    put_le((uint64_t)urgency, 1);
+    put_le(id, 4);
+    put_le(timeout, 4);
+    put_le(0, 8);
+}
+
+static long send_msg(void)
+{
+    long r = veridian_syscall2(SYS_NOTIFY, (long)msg, (long)msg_len);
+    if (r < 0) {
+        const char *why = "";
+        if (r == -8)
+            why = ": desktop not running";
+        else if (r == -4)
+            why = ": no such notification";
+        fprintf(stderr, "notify-send: failed (%ld)%s\n", r, why);
+        exit(1);
+    }
+    return r;
+}
+
+/* Request with only a header and empty strings (close, status). */
+static long simple_request(int type, uint32_t id)
+{
+    begin(type, 1, id, NOTIFY_TIMEOUT_DEFAULT);
+    put_string("", 0); /* app name */
+    put_string("", 0); /* summary */
+    put_string("", 0); /* body */
+    put_string("", 0); /* icon */
+    put_le(0, 1);      /* actions */
+    return send_msg();
+}
+
+static uint32_t parse_number(const char *arg)
+{
+    char *end;
+    unsigned long n = strtoul(arg, &end, 10);
+    if (*arg == '\0' || *end != '\0' || n > 0xFFFFFFFEul)
+        usage();
+    return (uint32_t)n;
+}
+
+static int parse_urgency(const char *arg)
+{
+    if (strcmp(arg, "low") == 0)
+        return 0;
+    if (strcmp(arg, "normal") == 0)
+        return 1;
+    if (strcmp(arg, "critical") == 0)
+        return 2;
+    usage();
+    return 1;
+}
+
+int main(int argc, char **argv)
+{
+    struct action actions[NOTIFY_MAX_ACTIONS];
+    const char *app = "notify-send", *icon = "", *summary, *body = "";
+    uint32_t timeout = NOTIFY_TIMEOUT_DEFAULT;
+    int urgency = 1, n_actions = 0, print_id = 0, wait = 0;
+    long id, status;
+    int i;
+
+    if (argc == 3 && strcmp(argv[1], "-c") == 0) {
+        simple_request(NOTIFY_MSG_DISMISS, parse_number(argv[2]));
+        return 0;
+    }
+
+    for (i = 1; i < argc && argv[i][0] == '-'; i++) {
+        if (strcmp(argv[i], "-u") == 0 && i + 1 < argc)
+            urgency = parse_urgency(argv[++i]);
+        else if (strcmp(argv[i], "-t") == 0 && i + 1 < argc)
+            timeout = parse_number(argv[++i]);
+        else if (strcmp(argv[i], "-i") == 0 && i + 1 < argc)
+            icon = argv[++i];
+        else if (strcmp(argv[i], "-a") == 0 && i + 1 < argc)
+            app = argv[++i];
+        else if (strcmp(argv[i], "-A") == 0 && i + 1 < argc) {
+            const char *arg = argv[++i];
+            const char *eq = strchr(arg, '=');
+            if (n_actions == NOTIFY_MAX_ACTIONS) {
+                fprintf(stderr, "notify-send: at most %d actions\n", NOTIFY_MAX_ACTIONS);
+                return 2;
+            }
+            /* Without a key the label doubles as the key */
+            actions[n_actions].key = arg;
+            actions[n_actions].key_len = eq ? (size_t)(eq - arg) : strlen(arg);
+            actions[n_actions].label = eq ? eq + 1 : arg;
+            n_actions++;
+        } else if (strcmp(argv[i], "-p") == 0)
+            print_id = 1;
+        else if (strcmp(argv[i], "-w") == 0)
+            wait = 1;
+        else
+            usage();
+    }
+    if (i == argc || argc - i > 2)
+        usage();
+    summary = argv[i];
+    if (i + 1 < argc)
+        body = argv[i + 1];
+
+    begin(NOTIFY_MSG_NOTIFY, urgency, 0, timeout);
+    put_string(app, strlen(app));
+    put_string(summary, strlen(summary));
+    put_string(body, strlen(body));
+    put_string(icon, strlen(icon));
+    put_le((uint64_t)n_actions, 1);
+    for (i = 0; i < n_actions; i++) {
+        put_string(actions[i].key, actions[i].key_len);
+        put_string(actions[i].label, strlen(actions[i].label));
+    }
+    id = send_msg();
+
+    if (print_id)
+        printf("%ld\n", id);
+    if (!wait)
+        return 0;
+
+    while ((status = simple_request(NOTIFY_MSG_STATUS, (uint32_t)id)) == NOTIFY_STATUS_ACTIVE)
+        usleep(100000);
+    if (status >= NOTIFY_STATUS_ACTION && status - NOTIFY_STATUS_ACTION < n_actions) {
+        const struct action *a = &actions[status - NOTIFY_STATUS_ACTION];
+        printf("%.*s\n", (int)a->key_len, a->key);
+    }
+    return 0;
+}
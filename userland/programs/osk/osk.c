@@ -0,0 +1,344 @@
+/*
+ * osk -- VeridianOS on-screen keyboard
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A US-layout keyboard in a desktop window for touch screens and for
+ * users who cannot use a physical keyboard. The window never takes the
+ * keyboard focus: clicking a key injects it with SYS_INPUT_INJECT into
+ * whichever window has the focus.
+ *
+ * Usage:
+ *   osk
+ *
+ * Shift, Ctrl, Alt and Super work like sticky keys: one click applies the
+ * modifier to the next key, a second click locks it, a third releases it.
+ * Injecting input needs root or the input-injection capability.
+ */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include <veridian/input.h>
+#include <veridian/window.h>
+
+#include "../sysmon/font8x16.h"
+
+/* Keys are laid out on a grid of half-key columns */
+#define HALF            20
+#define ROW_H           40
+#define GAP             4
+#define COLS            30
+#define ROWS            5
+#define WIDTH           (GAP + COLS * HALF)
+#define HEIGHT          (GAP + ROWS * ROW_H)
+#define FLASH_MS        120
+
+/* Colors (0x00RRGGBB) */
+#define C_BG            0x1E1E2E
+#define C_KEY           0x3C3C55
+#define C_SPECIAL       0x323248
+#define C_PRESSED       0x5A9BE6
+#define C_LATCHED       0x3C5A96
+#define C_LOCKED        0x50C878
+#define C_TEXT          0xDCDCE6
+#define C_ERROR         0xE66464
+
+struct key {
+    const char *label;
+    uint16_t code;
+    int w;              /* in half-key columns */
+};
+
+static const struct key row0[] = {
+    { "`", '`', 2 }, { "1", '1', 2 }, { "2", '2', 2 }, { "3", '3', 2 },
+    { "4", '4', 2 }, { "5", '5', 2 }, { "6", '6', 2 }, { "7", '7', 2 },
+    { "8", '8', 2 }, { "9", '9', 2 }, { "0", '0', 2 }, { "-", '-', 2 },
+    { "=", '=', 2 }, { "Bksp", '\b', 4 },
+};
+static const struct key row1[] = {
+    { "Tab", '\t', 3 }, { "q", 'q', 2 }, { "w", 'w', 2 }, { "e", 'e', 2 },
+    { "r", 'r', 2 }, { "t", 't', 2 }, { "y", 'y', 2 }, { "u", 'u', 2 },
+    { "i", 'i', 2 }, { "o", 'o', 2 }, { "p", 'p', 2 }, { "[", '[', 2 },
+    { "]", ']', 2 }, { "\\", '\\', 3 },
+};
+static const struct key row2[] = {
+    { "Ctrl", INPUT_KEY_MOD_CTRL, 4 }, { "a", 'a', 2 }, { "s", 's', 2 },
+    { "d", 'd', 2 }, { "f", 'f', 2 }, { "g", 'g', 2 }, { "h", 'h', 2 },
+    { "j", 'j', 2 }, { "k", 'k', 2 }, { "l", 'l', 2 }, { ";", ';', 2 },
+    { "'", '\'', 2 }, { "Enter", '\n', 4 },
+};
+static const struct key row3[] = {
+    { "Shift", INPUT_KEY_MOD_SHIFT, 5 }, { "z", 'z', 2 }, { "x", 'x', 2 },
+    { "c", 'c', 2 }, { "v", 'v', 2 }, { "b", 'b', 2 }, { "n", 'n', 2 },
+    { "m", 'm', 2 }, { ",", ',', 2 }, { ".", '.', 2 }, { "/", '/', 2 },
+    { "Up", INPUT_KEY_UP, 2 }, { "Del", INPUT_KEY_DELETE, 3 },
+};
+static const struct key row4[] = {
+    { "Alt", INPUT_KEY_MOD_ALT, 3 }, { "Super", INPUT_KEY_MOD_SUPER, 3 },
+    { "", ' ', 14 }, { "<", INPUT_KEY_LEFT, 2 }, { "Dn", INPUT_KEY_DOWN, 2 },
+    { ">", INPUT_KEY_RIGHT, 2 }, { "Home", INPUT_KEY_HOME, 2 },
+    { "End", INPUT_KEY_END, 2 },
+};
+
+#define ROW(r) { r, (int)(sizeof(r) / sizeof(r[0])) }
+
+static const struct {
+    const struct key *keys;
+    int n;
+} rows[ROWS] = { ROW(row0), ROW(row1), ROW(row2), ROW(row3), ROW(row4) };
+
+static uint32_t fb[WIDTH * HEIGHT];
+
+/* Modifier state, one bit per INPUT_KEY_MOD_* code */
+static unsigned latched, locked;
+/* Modifiers pressed with the last key, released on the next pass so the
+ * desktop sees them held for at least one frame (hotkeys) */
+static unsigned to_release;
+
+static const struct key *flash_key;
+static long flash_until;
+static int denied;
+
+static long now_ms(void)
+{
+    struct timespec ts;
+
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+static int is_modifier(uint16_t code)
+{
+    return code >= INPUT_KEY_MOD_SHIFT && code <= INPUT_KEY_MOD_SUPER;
+}
+
+static unsigned mod_bit(uint16_t code)
+{
+    return 1u << (code - INPUT_KEY_MOD_SHIFT);
+}
+
+/* ------------------------------------------------------------------------- */
+/* Injection                                                                 */
+/* ------------------------------------------------------------------------- */
+
+static void inject(const struct input_event *events, size_t n)
+{
+    long r = input_inject(events, n);
+
+    if (r < 0 && !denied) {
+        fprintf(stderr, "osk: cannot inject input (%ld)%s\n", r,
+                r == -3 ? ": needs root or the input-injection capability" : "");
+        denied = 1;
+    }
+}
+
+static void release_modifiers(void)
+{
+    struct input_event events[4];
+    size_t n = 0;
+    unsigned bit;
+
+    for (bit = 0; bit < 4; bit++) {
+        if (to_release & (1u << bit)) {
+            struct input_event ev = { 0, INPUT_EV_KEY, (uint16_t)(INPUT_KEY_MOD_SHIFT + bit), 0 };
+            events[n++] = ev;
+        }
+    }
+    to_release = 0;
+    if (n)
+        inject(events, n);
+}
+
+/* Send a key with the active modifiers held down. */
+static void send_key(uint16_t code)
+{
+    struct input_event events[5];
+    unsigned mods = latched | locked, bit;
+    size_t n = 0;
+
+    release_modifiers();
+    for (bit = 0; bit < 4; bit++) {
+        if (mods & (1u << bit)) {
+            struct input_event ev = { 0, INPUT_EV_KEY, (uint16_t)(INPUT_KEY_MOD_SHIFT + bit), 1 };
+            events[n++] = ev;
+        }
+    }
+    {
+        struct input_event ev = { 0, INPUT_EV_KEY, code, 1 };
+        events[n++] = ev;
+    }
+    inject(events, n);
+    to_release = mods;
+    latched = 0;
+}
+
+static void press(const struct key *k)
+{
+    if (is_modifier(k->code)) {
+        unsigned bit = mod_bit(k->code);
+        if (locked & bit) {
+            locked &= ~bit;
+        } else if (latched & bit) {
+            latched &= ~bit;
+            locked |= bit;
+        } else {
+            latched |= bit;
+        }
+        return;
+    }
+    send_key(k->code);
+    flash_key = k;
+    flash_until = now_ms() + FLASH_MS;
+}
+
+/* The key under content position (x, y), or NULL. */
+static const struct key *key_at(int x, int y)
+{
+    int r = (y - GAP / 2) / ROW_H, i, col = 0;
+
+    if (y < GAP / 2 || r >= ROWS)
+        return NULL;
+    for (i = 0; i < rows[r].n; i++) {
+        const struct key *k = &rows[r].keys[i];
+        int kx = GAP + col * HALF;
+        if (x >= kx - GAP / 2 && x < kx + k->w * HALF - GAP / 2)
+            return k;
+        col += k->w;
+    }
+    return NULL;
+}
+
+/* ------------------------------------------------------------------------- */
+/* Drawing                                                                   */
+/* ------------------------------------------------------------------------- */
+
+static void fill(int x, int y, int w, int h, uint32_t color)
+{
+    int i, j;
+
+    if (x + w > WIDTH)
+        w = WIDTH - x;
+    if (y + h > HEIGHT)
+        h = HEIGHT - y;
+    for (j = 0; j < h; j++)
+        for (i = 0; i < w; i++)
+            fb[(y + j) * WIDTH + x + i] = color;
+}
+
+static void text(int x, int y, const char *s, uint32_t color)
+{
+    int row, bit;
+
+    for (; *s && x + FONT_WIDTH <= WIDTH; s++) {
+        unsigned char c = (unsigned char)*s;
+        const unsigned char *glyph;
+        if (c < FONT_FIRST || c > FONT_LAST)
+            c = '?';
+        glyph = font8x16[c - FONT_FIRST];
+        for (row = 0; row < FONT_HEIGHT && y + row < HEIGHT; row++)
+            for (bit = 0; bit < FONT_WIDTH; bit++)
+                if (glyph[row] & (0x80 >> bit))
+                    fb[(y + row) * WIDTH + x + bit] = color;
+        x += FONT_WIDTH;
+    }
+}
+
+/* The legend shown on a key, upper case while Shift is active. */
+static void legend(const struct key *k, char *buf, size_t size)
+{
+    snprintf(buf, size, "%s", k->label);
+    if ((latched | locked) & mod_bit(INPUT_KEY_MOD_SHIFT) &&
+        k->code >= 'a' && k->code <= 'z')
+        buf[0] = (char)(k->code - 'a' + 'A');
+}
+
+static void draw(void)
+{
+    char label[8];
+    int r, i;
+
+    fill(0, 0, WIDTH, HEIGHT, denied ? C_ERROR : C_BG);
+    for (r = 0; r < ROWS; r++) {
+        int col = 0, y = GAP + r * ROW_H;
+        for (i = 0; i < rows[r].n; i++) {
+            const struct key *k = &rows[r].keys[i];
+            int x = GAP + col * HALF, w = k->w * HALF - GAP;
+            uint32_t color = k->code < 0x80 && k->w == 2 ? C_KEY : C_SPECIAL;
+
+            if (k == flash_key)
+                color = C_PRESSED;
+            else if (is_modifier(k->code) && (locked & mod_bit(k->code)))
+                color = C_LOCKED;
+            else if (is_modifier(k->code) && (latched & mod_bit(k->code)))
+                color = C_LATCHED;
+            fill(x, y, w, ROW_H - GAP, color);
+            legend(k, label, sizeof(label));
+            text(x + (w - (int)strlen(label) * FONT_WIDTH) / 2,
+                 y + (ROW_H - GAP - FONT_HEIGHT) / 2, label, C_TEXT);
+            col += k->w;
+        }
+    }
+}
+
+int main(void)
+{
+    struct win_event events[32];
+    long win, n = 0;
+    int running = 1, dirty = 1, i;
+
+    win = win_create(WIDTH, HEIGHT, "Keyboard");
+    if (win < 0) {
+        fprintf(stderr, "osk: cannot open window (%ld)%s\n", win,
+                win == -8 ? ": desktop not running" : "");
+        return 1;
+    }
+    win_set_flags(win, WIN_FLAG_NO_FOCUS);
+
+    while (running) {
+        if (to_release)
+            release_modifiers();
+        if (flash_key && now_ms() >= flash_until) {
+            flash_key = NULL;
+            dirty = 1;
+        }
+
+        while (running && (n = win_poll(win, events, 32)) > 0) {
+            for (i = 0; i < n && running; i++) {
+                const struct key *k;
+                switch (events[i].kind) {
+                case WIN_EVENT_CLOSE:
+                    running = 0;
+                    break;
+                case WIN_EVENT_BUTTON_PRESS:
+                    if (events[i].code == 0 && (k = key_at(events[i].x, events[i].y)))
+                        press(k);
+                    break;
+                default:
+                    continue;
+                }
+                dirty = 1;
+            }
+        }
+        if (n < 0) {
+            fprintf(stderr, "osk: window lost (%ld)\n", n);
+            return 1;
+        }
+
+        if (dirty && running) {
+            draw();
+            if (win_present(win, fb, sizeof(fb)) < 0)
+                return 1;
+            dirty = 0;
+        }
+        usleep(30000);
+    }
+
+    /* Do not leave modifiers held behind */
+    release_modifiers();
+    win_destroy(win);
+    return 0;
+}
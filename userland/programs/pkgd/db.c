@@ -0,0 +1,201 @@
+/*
+ * VeridianOS Package Service -- db.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Installed-package records. Each package has a text file
+ * /var/db/pkgd/<name>.manifest:
+ *
+ *   name <name>
+ *   version <version>
+ *   description <text>
+ *   license <license>
+ *   depends <name>                              (one line per dependency)
+ *   file <checksum> <mode> <size> <path>        (one line per file)
+ *
+ * checksum is hex FNV-1a, mode octal, size decimal; the path runs to the
+ * end of the line.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <dirent.h>
+
+#include "pkgd.h"
+
+#define RECORD_SUFFIX ".manifest"
+
+static int record_path(const char *name, char *buf, size_t cap)
+{
+    if ((size_t)snprintf(buf, cap, "%s/%s%s", PKGD_DB_DIR, name,
+                         RECORD_SUFFIX) >= cap)
+        return -ENAMETOOLONG;
+    return 0;
+}
+
+static void copy_field(char *dst, size_t cap, const char *src, size_t len)
+{
+    if (len >= cap)
+        len = cap - 1;
+    memcpy(dst, src, len);
+    dst[len] = '\0';
+}
+
+int db_load(const char *name, pkgd_record_t *rec)
+{
+    char path[PKGD_MAX_PATH];
+    uint8_t *data;
+    size_t len;
+    int ret;
+
+    memset(rec, 0, sizeof(*rec));
+    if ((ret = record_path(name, path, sizeof(path))) < 0)
+        return ret;
+    if ((ret = pkgd_read_file(path, &data, &len)) < 0)
+        return ret;
+
+    rec->files = calloc(PKGD_MAX_FILES, sizeof(pkgd_file_t));
+    if (!rec->files) {
+        free(data);
+        return -ENOMEM;
+    }
+
+    pkgd_manifest_t *m = &rec->manifest;
+    const char *p = (const char *)data, *end = p + len;
+    while (p < end) {
+        const char *eol = memchr(p, '\n', (size_t)(end - p));
+        if (!eol)
+            eol = end;
+        const char *sp = memchr(p, ' ', (size_t)(eol - p));
+        if (sp) {
+            size_t klen = (size_t)(sp - p);
+            const char *v = sp + 1;
+            size_t vlen = (size_t)(eol - v);
+
+            if (klen == 4 && !memcmp(p, "name", 4)) {
+                copy_field(m->name, sizeof(m->name), v, vlen);
+            } else if (klen == 7 && !memcmp(p, "version", 7)) {
+                copy_field(m->version, sizeof(m->version), v, vlen);
+            } else if (klen == 11 && !memcmp(p, "description", 11)) {
+                copy_field(m->description, sizeof(m->description), v, vlen);
+            } else if (klen == 7 && !memcmp(p, "license", 7)) {
+                copy_field(m->license, sizeof(m->license), v, vlen);
+            } else if (klen == 7 && !memcmp(p, "depends", 7) &&
+                       m->ndeps < PKGD_MAX_DEPS) {
+                copy_field(m->depends[m->ndeps++], PKGD_MAX_NAME, v, vlen);
+            } else if (klen == 4 && !memcmp(p, "file", 4) &&
+                       rec->nfiles < PKGD_MAX_FILES) {
+                pkgd_file_t *f = &rec->files[rec->nfiles];
+                char *q;
+                f->checksum = (uint32_t)strtoul(v, &q, 16);
+                f->mode = (uint32_t)strtoul(q, &q, 8);
+                f->size = strtoull(q, &q, 10);
+                if (q < eol && *q == ' ') {
+                    q++;
+                    copy_field(f->path, sizeof(f->path), q,
+                               (size_t)(eol - q));
+                    rec->nfiles++;
+                }
+            }
+        }
+        p = eol + 1;
+    }
+
+    free(data);
+    if (strcmp(m->name, name) != 0) {
+        db_free(rec);
+        return -EPROTO;
+    }
+    return 0;
+}
+
+int db_save(const pkgd_record_t *rec)
+{
+    const pkgd_manifest_t *m = &rec->manifest;
+    char path[PKGD_MAX_PATH];
+    char tmp[PKGD_MAX_PATH + 8];
+    int ret;
+
+    if ((ret = record_path(m->name, path, sizeof(path))) < 0)
+        return ret;
+    snprintf(tmp, sizeof(tmp), "%s.new", path);
+
+    size_t cap = 1024 + (size_t)rec->nfiles * (PKGD_MAX_PATH + 48);
+    char *buf = malloc(cap);
+    if (!buf)
+        return -ENOMEM;
+
+    size_t n = (size_t)snprintf(buf, cap,
+                                "name %s\nversion %s\ndescription %s\nlicense %s\n",
+                                m->name, m->version, m->description, m->license);
+    for (int i = 0; i < m->ndeps; i++)
+        n += (size_t)snprintf(buf + n, cap - n, "depends %s\n", m->depends[i]);
+    for (int i = 0; i < rec->nfiles; i++) {
+        const pkgd_file_t *f = &rec->files[i];
+        n += (size_t)snprintf(buf + n, cap - n, "file %08x %o %llu %s\n",
+                              (unsigned)f->checksum, (unsigned)f->mode,
+                              (unsigned long long)f->size, f->path);
+    }
+
+    /* Write-then-rename so a crash never leaves a half-written record */
+    ret = pkgd_write_file(tmp, (const uint8_t *)buf, n, 0644);
+    free(buf);
+    if (ret < 0)
+        return ret;
+    if (rename(tmp, path) < 0)
+        return -errno;
+    return 0;
+}
+
+int db_delete(const char *name)
+{
+    char path[PKGD_MAX_PATH];
+    int ret = record_path(name, path, sizeof(path));
+    if (ret < 0)
+        return ret;
+    if (unlink(path) < 0)
+        return -errno;
+    return 0;
+}
+
+int db_is_installed(const char *name)
+{
+    char path[PKGD_MAX_PATH];
+    if (record_path(name, path, sizeof(path)) < 0)
+        return 0;
+    return access(path, F_OK) == 0;
+}
+
+/* Fill `names` with the installed package names. Returns the count. */
+int db_names(char names[][PKGD_MAX_NAME], int max)
+{
+    DIR *dir = opendir(PKGD_DB_DIR);
+    struct dirent *ent;
+    int n = 0;
+    size_t slen = strlen(RECORD_SUFFIX);
+
+    if (!dir)
+        return errno == ENOENT ? 0 : -errno;
+
+    while (n < max && (ent = readdir(dir)) != NULL) {
+        size_t len = strlen(ent->d_name);
+        if (len <= slen || len - slen >= PKGD_MAX_NAME ||
+            strcmp(ent->d_name + len - slen, RECORD_SUFFIX) != 0)
+            continue;
+        copy_field(names[n++], PKGD_MAX_NAME, ent->d_name, len - slen);
+    }
+
+    closedir(dir);
+    return n;
+}
+
+void db_free(pkgd_record_t *rec)
+{
+    free(rec->files);
+    rec->files = NULL;
+    rec->nfiles = 0;
+}
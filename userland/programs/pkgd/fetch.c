@@ -0,0 +1,87 @@
+/*
+ * VeridianOS Package Service -- fetch.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Package retrieval. A package spec is one of:
+ *   /path/to/pkg.vpk       a local archive, used in place
+ *   file:///path/pkg.vpk   the same, as a URL
+ *   name                   <repository>/<name>.vpk, copied into the cache
+ *
+ * The repository directory is read from /etc/pkgd.conf
+ * ("repository = /some/dir") and defaults to /var/cache/pkgd/repo.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+
+#include "pkgd.h"
+
+static void repository_dir(char *dir, size_t cap)
+{
+    uint8_t *conf;
+    size_t len;
+
+    snprintf(dir, cap, "%s", PKGD_DEFAULT_REPO);
+    if (pkgd_read_file(PKGD_CONF_FILE, &conf, &len) < 0)
+        return;
+
+    const char *p = (const char *)conf, *end = p + len;
+    while (p < end) {
+        const char *eol = memchr(p, '\n', (size_t)(end - p));
+        if (!eol)
+            eol = end;
+        while (p < eol && (*p == ' ' || *p == '\t'))
+            p++;
+        if ((size_t)(eol - p) > 10 && !memcmp(p, "repository", 10)) {
+            p += 10;
+            while (p < eol && (*p == ' ' || *p == '\t' || *p == '='))
+                p++;
+            const char *v = p;
+            while (p < eol && *p != ' ' && *p != '\t' && *p != '\r')
+                p++;
+            if (p > v && (size_t)(p - v) < cap) {
+                memcpy(dir, v, (size_t)(p - v));
+                dir[p - v] = '\0';
+            }
+        }
+        p = eol + 1;
+    }
+    free(conf);
+}
+
+int fetch_package(const char *spec, char *path, size_t cap)
+{
+    char repo[PKGD_MAX_PATH];
+    char src[PKGD_MAX_PATH];
+    uint8_t *data;
+    size_t len;
+    int ret;
+
+    if (!strncmp(spec, "file://", 7))
+        spec += 7;
+    else if (strstr(spec, "://"))
+        return -EPROTONOSUPPORT;
+
+    if (strchr(spec, '/')) {
+        if ((size_t)snprintf(path, cap, "%s", spec) >= cap)
+            return -ENAMETOOLONG;
+        return 0;
+    }
+
+    repository_dir(repo, sizeof(repo));
+    if ((size_t)snprintf(src, sizeof(src), "%s/%s.vpk", repo, spec) >=
+            sizeof(src) ||
+        (size_t)snprintf(path, cap, "%s/%s.vpk", PKGD_CACHE_DIR, spec) >= cap)
+        return -ENAMETOOLONG;
+
+    ret = pkgd_read_file(src, &data, &len);
+    if (ret < 0)
+        return ret;
+    ret = pkgd_write_file(path, data, len, 0644);
+    free(data);
+    return ret;
+}
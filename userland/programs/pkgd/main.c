@@ -0,0 +1,355 @@
+/*
+ * VeridianOS Package Service -- pkgd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * User-space package service. Binds the "pkgd" IPC endpoint and serves
+ * fetch/install/remove/upgrade/list/verify requests forwarded by the
+ * kernel shell's `pkg` builtin.
+ *
+ * Each request is a large message: the opcode selects the operation, the
+ * header flags carry a sequence number and the payload is a list of
+ * NUL-terminated arguments. The reply is a small message to the kernel's
+ * "pkgd.reply" endpoint carrying { seq, status, count }, where status is
+ * 0 or a negated errno. Progress output goes to the console.
+ *
+ * pkgd also answers init's liveness pings (INIT_OP_PING, see
+ * <veridian/init.h>) on the same endpoint.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <fcntl.h>
+#include <errno.h>
+#include <sys/stat.h>
+#include <veridian/init.h>
+#include <veridian/syscall.h>
+
+#include "pkgd.h"
+
+#define MAX_ARGS 32
+
+/* ========================================================================= */
+/* Utility functions                                                         */
+/* ========================================================================= */
+
+int pkgd_read_file(const char *path, uint8_t **out, size_t *len)
+{
+    int fd = open(path, O_RDONLY);
+    if (fd < 0)
+        return -errno;
+
+    size_t cap = 4096, used = 0;
+    uint8_t *buf = malloc(cap);
+    if (!buf) {
+        close(fd);
+        return -ENOMEM;
+    }
+
+    for (;;) {
+        if (used == cap) {
+            uint8_t *grown = realloc(buf, cap * 2);
+            if (!grown) {
+                free(buf);
+                close(fd);
+                return -ENOMEM;
+            }
+            buf = grown;
+            cap *= 2;
+        }
+        ssize_t n = read(fd, buf + used, cap - used);
+        if (n < 0) {
+            int err = errno;
+            free(buf);
+            close(fd);
+            return -err;
+        }
+        if (n == 0)
+            break;
+        used += (size_t)n;
+    }
+
+    close(fd);
+    *out = buf;
+    *len = used;
+    return 0;
+}
+
+int pkgd_write_file(const char *path, const uint8_t *data, size_t len,
+                    uint32_t mode)
+{
+    int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, (mode_t)(mode & 07777));
+    if (fd < 0)
+        return -errno;
+
+    size_t done = 0;
+    while (done < len) {
+        ssize_t n = write(fd, data + done, len - done);
+        if (n <= 0) {
+            int err = n < 0 ? errno : EIO;
+            close(fd);
+            return -err;
+        }
+        done += (size_t)n;
+    }
+
+    if (close(fd) < 0)
+        return -errno;
+    return 0;
+}
+
+/* Create every missing parent directory of `path` (and `path` itself if it
+ * ends in '/'). */
+int pkgd_mkdirs(const char *path)
+{
+    char buf[PKGD_MAX_PATH];
+    size_t len = strlen(path);
+    if (len >= sizeof(buf))
+        return -ENAMETOOLONG;
+    memcpy(buf, path, len + 1);
+
+    for (size_t i = 1; i < len; i++) {
+        if (buf[i] != '/')
+            continue;
+        buf[i] = '\0';
+        if (mkdir(buf, 0755) < 0 && errno != EEXIST) {
+            int err = errno;
+            buf[i] = '/';
+            return -err;
+        }
+        buf[i] = '/';
+    }
+    return 0;
+}
+
+/* FNV-1a, used for the per-file checksums in package records */
+uint32_t pkgd_checksum(const uint8_t *data, size_t len)
+{
+    uint32_t hash = 2166136261u;
+    for (size_t i = 0; i < len; i++) {
+        hash ^= data[i];
+        hash *= 16777619u;
+    }
+    return hash;
+}
+
+/* Compare dotted numeric versions: <0, 0 or >0 like strcmp */
+int pkgd_version_cmp(const char *a, const char *b)
+{
+    while (*a || *b) {
+        unsigned long x = strtoul(a, (char **)&a, 10);
+        unsigned long y = strtoul(b, (char **)&b, 10);
+        if (x != y)
+            return x < y ? -1 : 1;
+        while (*a && *a != '.')
+            a++;
+        while (*b && *b != '.')
+            b++;
+        if (*a == '.')
+            a++;
+        if (*b == '.')
+            b++;
+    }
+    return 0;
+}
+
+/* ========================================================================= */
+/* Request handlers                                                          */
+/* ========================================================================= */
+
+static long do_fetch(int argc, char **argv, long *count)
+{
+    char path[PKGD_MAX_PATH];
+
+    for (int i = 0; i < argc; i++) {
+        int ret = fetch_package(argv[i], path, sizeof(path));
+        if (ret < 0) {
+            printf("pkgd: cannot fetch %s (error %d)\n", argv[i], -ret);
+            return ret;
+        }
+        printf("pkgd: fetched %s -> %s\n", argv[i], path);
+        (*count)++;
+    }
+    return 0;
+}
+
+static long do_install(int argc, char **argv, long *count)
+{
+    for (int i = 0; i < argc; i++) {
+        int ret = txn_install(argv[i], 0, 0);
+        if (ret < 0)
+            return ret;
+        *count += ret;
+    }
+    return 0;
+}
+
+static long do_remove(int argc, char **argv, long *count)
+{
+    for (int i = 0; i < argc; i++) {
+        int ret = txn_remove(argv[i]);
+        if (ret < 0)
+            return ret;
+        (*count)++;
+    }
+    return 0;
+}
+
+static long do_upgrade(int argc, char **argv, long *count)
+{
+    static char names[PKGD_MAX_PACKAGES][PKGD_MAX_NAME];
+    int n = argc;
+
+    if (argc == 0) {
+        n = db_names(names, PKGD_MAX_PACKAGES);
+        if (n < 0)
+            return n;
+    }
+
+    for (int i = 0; i < n; i++) {
+        const char *name = argc ? argv[i] : names[i];
+        int ret = txn_install(name, 1, 0);
+        if (ret < 0)
+            return ret;
+        *count += ret;
+    }
+    if (*count == 0)
+        printf("pkgd: all packages are up to date\n");
+    return 0;
+}
+
+static long do_list(long *count)
+{
+    static char names[PKGD_MAX_PACKAGES][PKGD_MAX_NAME];
+    int n = db_names(names, PKGD_MAX_PACKAGES);
+    if (n < 0)
+        return n;
+
+    printf("%-24s %-12s %s\n", "NAME", "VERSION", "DESCRIPTION");
+    for (int i = 0; i < n; i++) {
+        pkgd_record_t rec;
+        if (db_load(names[i], &rec) < 0)
+            continue;
+        printf("%-24s %-12s %s\n", rec.manifest.name, rec.manifest.version,
+               rec.manifest.description);
+        db_free(&rec);
+        (*count)++;
+    }
+    printf("%ld package(s) installed\n", *count);
+    return 0;
+}
+
+static long do_verify(int argc, char **argv, long *count)
+{
+    static char names[PKGD_MAX_PACKAGES][PKGD_MAX_NAME];
+    int n = argc;
+    int bad = 0;
+
+    if (argc == 0) {
+        n = db_names(names, PKGD_MAX_PACKAGES);
+        if (n < 0)
+            return n;
+    }
+
+    for (int i = 0; i < n; i++) {
+        int ret = txn_verify(argc ? argv[i] : names[i]);
+        if (ret < 0)
+            return ret;
+        bad += ret;
+        (*count)++;
+    }
+    return bad ? -EIO : 0;
+}
+
+static long dispatch(uint32_t op, int argc, char **argv, long *count)
+{
+    switch (op) {
+    case PKGD_OP_FETCH:   return do_fetch(argc, argv, count);
+    case PKGD_OP_INSTALL: return do_install(argc, argv, count);
+    case PKGD_OP_REMOVE:  return do_remove(argc, argv, count);
+    case PKGD_OP_UPGRADE: return do_upgrade(argc, argv, count);
+    case PKGD_OP_LIST:    return do_list(count);
+    case PKGD_OP_VERIFY:  return do_verify(argc, argv, count);
+    default:              return -ENOSYS;
+    }
+}
+
+/* ========================================================================= */
+/* IPC loop                                                                  */
+/* ========================================================================= */
+
+static long reply_cap = -1;
+
+static void send_reply(uint32_t op, uint32_t seq, long status, long count)
+{
+    /* The kernel binds its reply endpoint before sending the first request */
+    if (reply_cap < 0)
+        reply_cap = veridian_syscall1(SYS_IPC_LOOKUP_ENDPOINT,
+                                      PKGD_REPLY_SERVICE);
+    if (reply_cap < 0) {
+        printf("pkgd: no reply endpoint (error %ld)\n", -reply_cap);
+        return;
+    }
+
+    pkgd_small_msg_t msg;
+    memset(&msg, 0, sizeof(msg));
+    msg.capability = (uint64_t)reply_cap;
+    msg.opcode = op;
+    msg.data[0] = seq;
+    msg.data[1] = (uint64_t)status;
+    msg.data[2] = (uint64_t)count;
+    veridian_syscall4(SYS_IPC_SEND, reply_cap, &msg, sizeof(msg), 0);
+}
+
+int main(void)
+{
+    static uint8_t buf[sizeof(pkgd_msg_header_t) + PKGD_REQUEST_MAX + 1];
+    char *argv[MAX_ARGS];
+
+    long cap = veridian_syscall1(SYS_IPC_CREATE_ENDPOINT, 0);
+    if (cap < 0) {
+        printf("pkgd: cannot create endpoint (error %ld)\n", -cap);
+        return 1;
+    }
+    long ret = veridian_syscall2(SYS_IPC_BIND_ENDPOINT, cap, PKGD_SERVICE);
+    if (ret < 0) {
+        printf("pkgd: cannot bind \"%s\" (error %ld)\n", PKGD_SERVICE, -ret);
+        return 1;
+    }
+
+    pkgd_mkdirs(PKGD_ROLLBACK_DIR "/");
+    pkgd_mkdirs(PKGD_CACHE_DIR "/");
+    txn_recover();
+
+    for (;;) {
+        long n = veridian_syscall2(SYS_IPC_RECEIVE, cap, buf);
+        if (n < (long)sizeof(pkgd_msg_header_t))
+            continue;
+
+        pkgd_msg_header_t hdr;
+        memcpy(&hdr, buf, sizeof(hdr));
+        if (hdr.opcode == INIT_OP_PING) {
+            init_heartbeat();
+            continue;
+        }
+        size_t len = (size_t)n - sizeof(hdr);
+        if (len > PKGD_REQUEST_MAX)
+            len = PKGD_REQUEST_MAX;
+
+        char *payload = (char *)buf + sizeof(hdr);
+        payload[len] = '\0';
+
+        int argc = 0;
+        for (size_t off = 0; off < len && argc < MAX_ARGS;) {
+            argv[argc++] = payload + off;
+            off += strlen(payload + off) + 1;
+        }
+
+        long count = 0;
+        long status = dispatch(hdr.opcode, argc, argv, &count);
+        send_reply(hdr.opcode, hdr.flags, status, count);
+    }
+}
@@ -0,0 +1,172 @@
+/*
+ * VeridianOS Package Service -- pkgd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Shared types and function declarations for the pkgd service.
+ */
+
+#ifndef PKGD_H
+#define PKGD_H
+
+#include <stdint.h>
+#include <stddef.h>
+
+/* ========================================================================= */
+/* IPC protocol (mirrors kernel/src/pkg/pkgd_client.rs)                      */
+/* ========================================================================= */
+
+#define PKGD_SERVICE        "pkgd"
+#define PKGD_REPLY_SERVICE  "pkgd.reply"
+
+#define PKGD_OP_FETCH       1
+#define PKGD_OP_INSTALL     2
+#define PKGD_OP_REMOVE      3
+#define PKGD_OP_UPGRADE     4
+#define PKGD_OP_LIST        5
+#define PKGD_OP_VERIFY      6
+
+#define PKGD_REQUEST_MAX    1024
+
+/* Header the kernel writes in front of a large message's payload */
+typedef struct {
+    uint64_t capability;
+    uint32_t opcode;
+    uint32_t flags;         /* request sequence number */
+    uint64_t total_size;    /* payload length */
+    uint32_t checksum;
+    uint32_t reserved;
+} pkgd_msg_header_t;
+
+/* Small IPC message, used for replies */
+typedef struct {
+    uint64_t capability;
+    uint32_t opcode;
+    uint32_t flags;
+    uint64_t data[4];       /* seq, status, count, 0 */
+} pkgd_small_msg_t;
+
+/* ========================================================================= */
+/* Paths                                                                     */
+/* ========================================================================= */
+
+#define PKGD_CONF_FILE      "/etc/pkgd.conf"
+#define PKGD_DEFAULT_REPO   "/var/cache/pkgd/repo"
+#define PKGD_CACHE_DIR      "/var/cache/pkgd"
+#define PKGD_DB_DIR         "/var/db/pkgd"
+#define PKGD_ROLLBACK_DIR   "/var/db/pkgd/rollback"
+#define PKGD_JOURNAL_FILE   "/var/db/pkgd/journal"
+
+/* A package file at this path is a kernel, staged in the A/B boot slots */
+#define PKGD_KERNEL_PATH    "/boot/veridian-kernel"
+
+/* Sandbox profiles (mirrors kernel/src/security/sandbox.rs). Packages below
+ * core trust get <name>.toml here and may not ship files in it. */
+#define PKGD_SANDBOX_DIR    "/etc/sandbox"
+#define PKGD_TRUST_CORE     3
+
+/* ========================================================================= */
+/* .vpk archive format (mirrors kernel/src/pkg/vpk.rs)                       */
+/* ========================================================================= */
+
+#define VPK_MAGIC           "VPK1"
+#define VPK_VERSION         1
+#define VPK_HEADER_SIZE     48
+#define VPK_SIGNATURE_SIZE  64
+
+#define VPK_COMPRESS_NONE   0
+#define VPK_COMPRESS_LZ4    2
+
+/* ========================================================================= */
+/* Limits                                                                    */
+/* ========================================================================= */
+
+#define PKGD_MAX_NAME       65
+#define PKGD_MAX_VERSION    32
+#define PKGD_MAX_DESC       128
+#define PKGD_MAX_LICENSE    32
+#define PKGD_MAX_DEPS       16
+#define PKGD_MAX_FILES      512
+#define PKGD_MAX_PATH       256
+#define PKGD_MAX_PACKAGES   256
+#define PKGD_MAX_DEPTH      8
+
+/* ========================================================================= */
+/* Data structures                                                           */
+/* ========================================================================= */
+
+/* [package] table of a .vpk manifest */
+typedef struct {
+    char name[PKGD_MAX_NAME];
+    char version[PKGD_MAX_VERSION];
+    char description[PKGD_MAX_DESC];
+    char license[PKGD_MAX_LICENSE];
+    int  ndeps;
+    char depends[PKGD_MAX_DEPS][PKGD_MAX_NAME];
+} pkgd_manifest_t;
+
+/* One installed file */
+typedef struct {
+    char     path[PKGD_MAX_PATH];
+    uint64_t size;
+    uint32_t mode;
+    uint32_t checksum;
+} pkgd_file_t;
+
+/* Installed package record (/var/db/pkgd/<name>.manifest) */
+typedef struct {
+    pkgd_manifest_t manifest;
+    int             nfiles;
+    pkgd_file_t    *files;
+} pkgd_record_t;
+
+/* A loaded, verified archive */
+typedef struct {
+    uint8_t        *data;       /* whole .vpk file */
+    size_t          len;
+    const char     *manifest_text;  /* points into data */
+    size_t          manifest_len;
+    pkgd_manifest_t manifest;
+    uint8_t        *table;      /* decompressed file table */
+    size_t          table_len;
+    int             trust;      /* signer trust level, -1 if unsigned */
+} pkgd_vpk_t;
+
+/* ========================================================================= */
+/* Function declarations                                                     */
+/* ========================================================================= */
+
+/* util (main.c) */
+int      pkgd_read_file(const char *path, uint8_t **out, size_t *len);
+int      pkgd_write_file(const char *path, const uint8_t *data, size_t len,
+                         uint32_t mode);
+int      pkgd_mkdirs(const char *path);
+uint32_t pkgd_checksum(const uint8_t *data, size_t len);
+int      pkgd_version_cmp(const char *a, const char *b);
+
+/* vpk.c */
+int  vpk_load(const char *path, pkgd_vpk_t *vpk);
+void vpk_free(pkgd_vpk_t *vpk);
+int  vpk_next_file(const pkgd_vpk_t *vpk, size_t *pos, pkgd_file_t *file,
+                   const uint8_t **data);
+int  manifest_parse(const char *text, size_t len, pkgd_manifest_t *m);
+
+/* fetch.c */
+int fetch_package(const char *spec, char *path, size_t cap);
+
+/* db.c */
+int  db_load(const char *name, pkgd_record_t *rec);
+int  db_save(const pkgd_record_t *rec);
+int  db_delete(const char *name);
+int  db_is_installed(const char *name);
+int  db_names(char names[][PKGD_MAX_NAME], int max);
+void db_free(pkgd_record_t *rec);
+
+/* txn.c */
+int txn_install(const char *spec, int upgrade, int depth);
+int txn_remove(const char *name);
+int txn_verify(const char *name);
+int txn_recover(void);
+
+#endif /* PKGD_H */
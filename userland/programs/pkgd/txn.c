@@ -0,0 +1,656 @@
+/*
+ * VeridianOS Package Service -- txn.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Install, upgrade and remove transactions with rollback.
+ *
+ * Every change to the filesystem is first appended to a write-ahead
+ * journal (/var/db/pkgd/journal):
+ *
+ *   N <path>        a new file is about to be created
+ *   B <seq> <path>  an existing file is about to be moved to
+ *                   /var/db/pkgd/rollback/<seq>
+ *
+ * Overwritten or deleted files (including the package record itself) are
+ * moved aside rather than destroyed. Committing deletes the journal and
+ * the rollback directory; a failure undoes the journal in reverse order.
+ * A journal found at startup belongs to an interrupted transaction and is
+ * undone the same way.
+ *
+ * A package below core trust is sandboxed: before its files are placed,
+ * PKGD_SANDBOX_DIR/<name>.toml is written naming its executables and
+ * granting only what the [sandbox] table of its manifest asks for (read,
+ * write, services, network; see kernel/src/security/sandbox.rs). The
+ * profile is recorded as one of the package's files, so removal, upgrade
+ * and rollback handle it like any other.
+ *
+ * A package that ships PKGD_KERNEL_PATH also hands the kernel image to the
+ * kernel's A/B boot slots as the last step before committing. That write
+ * goes to the inactive slot and cannot be undone, but it only takes effect
+ * if the new kernel boots and init commits it.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <fcntl.h>
+#include <errno.h>
+#include <dirent.h>
+#include <veridian/syscall.h>
+
+#include "pkgd.h"
+
+static const char *trust_names[] = { "untrusted", "community", "developer",
+                                     "core" };
+
+/* ========================================================================= */
+/* Journal                                                                   */
+/* ========================================================================= */
+
+static int journal_fd = -1;
+static int backup_seq;
+
+static void backup_path(int seq, char *buf, size_t cap)
+{
+    snprintf(buf, cap, "%s/%d", PKGD_ROLLBACK_DIR, seq);
+}
+
+static int journal_begin(void)
+{
+    journal_fd = open(PKGD_JOURNAL_FILE, O_WRONLY | O_CREAT | O_TRUNC, 0600);
+    if (journal_fd < 0)
+        return -errno;
+    backup_seq = 0;
+    return 0;
+}
+
+static int journal_add(char kind, int seq, const char *path)
+{
+    char line[PKGD_MAX_PATH + 32];
+    int n = kind == 'B' ? snprintf(line, sizeof(line), "B %d %s\n", seq, path)
+                        : snprintf(line, sizeof(line), "N %s\n", path);
+    if (write(journal_fd, line, (size_t)n) != n)
+        return -EIO;
+    return 0;
+}
+
+/* Move `path` aside so it can be restored on rollback. */
+static int journal_backup(const char *path)
+{
+    char backup[PKGD_MAX_PATH];
+    int seq = backup_seq++;
+    int ret;
+
+    backup_path(seq, backup, sizeof(backup));
+    if ((ret = journal_add('B', seq, path)) < 0)
+        return ret;
+    if (rename(path, backup) < 0)
+        return -errno;
+    return 0;
+}
+
+static void clean_rollback_dir(void)
+{
+    char path[PKGD_MAX_PATH];
+    DIR *dir = opendir(PKGD_ROLLBACK_DIR);
+    struct dirent *ent;
+
+    if (!dir)
+        return;
+    while ((ent = readdir(dir)) != NULL) {
+        if (ent->d_name[0] == '.')
+            continue;
+        snprintf(path, sizeof(path), "%s/%s", PKGD_ROLLBACK_DIR, ent->d_name);
+        unlink(path);
+    }
+    closedir(dir);
+}
+
+static void journal_commit(void)
+{
+    close(journal_fd);
+    journal_fd = -1;
+    unlink(PKGD_JOURNAL_FILE);
+    clean_rollback_dir();
+}
+
+/* Undo every journaled change, newest first. */
+static void journal_undo(void)
+{
+    uint8_t *data;
+    size_t len;
+
+    if (journal_fd >= 0) {
+        close(journal_fd);
+        journal_fd = -1;
+    }
+    if (pkgd_read_file(PKGD_JOURNAL_FILE, &data, &len) < 0)
+        return;
+
+    /* Split into lines, then walk them backwards */
+    char *text = (char *)data;
+    char **lines = malloc((len + 1) * sizeof(char *));
+    int nlines = 0;
+    if (lines) {
+        for (size_t i = 0; i < len;) {
+            lines[nlines++] = text + i;
+            while (i < len && text[i] != '\n')
+                i++;
+            if (i < len)
+                text[i++] = '\0';
+        }
+    }
+
+    for (int i = nlines - 1; i >= 0; i--) {
+        char *line = lines[i];
+        char backup[PKGD_MAX_PATH];
+
+        if (line[0] == 'N' && line[1] == ' ') {
+            unlink(line + 2);
+        } else if (line[0] == 'B' && line[1] == ' ') {
+            char *path;
+            int seq = (int)strtol(line + 2, &path, 10);
+            if (*path != ' ')
+                continue;
+            backup_path(seq, backup, sizeof(backup));
+            /* Absent if we failed before the rename happened */
+            if (access(backup, F_OK) == 0) {
+                unlink(path + 1);
+                rename(backup, path + 1);
+            }
+        }
+    }
+
+    free(lines);
+    free(data);
+    unlink(PKGD_JOURNAL_FILE);
+    clean_rollback_dir();
+}
+
+int txn_recover(void)
+{
+    if (access(PKGD_JOURNAL_FILE, F_OK) != 0)
+        return 0;
+    printf("pkgd: rolling back interrupted transaction\n");
+    journal_undo();
+    return 1;
+}
+
+/* ========================================================================= */
+/* Helpers                                                                   */
+/* ========================================================================= */
+
+static int record_file(const char *name, char *buf, size_t cap)
+{
+    if ((size_t)snprintf(buf, cap, "%s/%s.manifest", PKGD_DB_DIR, name) >= cap)
+        return -ENAMETOOLONG;
+    return 0;
+}
+
+/* Journal the package record so rollback restores (or removes) it. */
+static int journal_record(const char *name)
+{
+    char path[PKGD_MAX_PATH];
+    int ret = record_file(name, path, sizeof(path));
+    if (ret < 0)
+        return ret;
+    if (access(path, F_OK) == 0)
+        return journal_backup(path);
+    return journal_add('N', 0, path);
+}
+
+/* Write one archive member, journaling what it replaces. */
+static int place_file(const pkgd_file_t *f, const uint8_t *data)
+{
+    int ret = pkgd_mkdirs(f->path);
+    if (ret < 0)
+        return ret;
+    if (access(f->path, F_OK) == 0)
+        ret = journal_backup(f->path);
+    else
+        ret = journal_add('N', 0, f->path);
+    if (ret < 0)
+        return ret;
+    return pkgd_write_file(f->path, data, (size_t)f->size, f->mode);
+}
+
+/*
+ * Stage a packaged kernel in the inactive boot slot. It boots on the next
+ * restart and is rolled back unless init commits it. Without an A/B boot
+ * disk the file is only installed.
+ */
+static int stage_kernel(const char *name, const uint8_t *image, uint64_t size)
+{
+    long slot = veridian_syscall2(SYS_BOOT_SLOT_INSTALL, image, size);
+
+    if (slot == -ENOENT) {
+        printf("pkgd: %s: no A/B boot disk, kernel not staged\n", name);
+        return 0;
+    }
+    if (slot == -EAGAIN) {
+        printf("pkgd: %s: the running kernel is not committed yet\n", name);
+        return -EAGAIN;
+    }
+    if (slot < 0) {
+        printf("pkgd: %s: cannot stage kernel (error %ld)\n", name, -slot);
+        return (int)slot;
+    }
+    printf("pkgd: %s: kernel staged in boot slot %c, reboot to try it\n",
+           name, 'A' + (int)slot);
+    return 0;
+}
+
+/* Name of another installed package that owns `path`, if any. */
+static int find_owner(const char *path, const char *self, char *owner)
+{
+    static char names[PKGD_MAX_PACKAGES][PKGD_MAX_NAME];
+    int n = db_names(names, PKGD_MAX_PACKAGES);
+
+    for (int i = 0; i < n; i++) {
+        pkgd_record_t rec;
+        if (!strcmp(names[i], self) || db_load(names[i], &rec) < 0)
+            continue;
+        for (int j = 0; j < rec.nfiles; j++) {
+            if (!strcmp(rec.files[j].path, path)) {
+                strcpy(owner, names[i]);
+                db_free(&rec);
+                return 1;
+            }
+        }
+        db_free(&rec);
+    }
+    return 0;
+}
+
+static int has_file(const pkgd_record_t *rec, const char *path)
+{
+    for (int i = 0; i < rec->nfiles; i++)
+        if (!strcmp(rec->files[i].path, path))
+            return 1;
+    return 0;
+}
+
+/* ========================================================================= */
+/* Sandbox profiles                                                          */
+/* ========================================================================= */
+
+static int in_sandbox_dir(const char *path)
+{
+    size_t n = strlen(PKGD_SANDBOX_DIR);
+    return !strncmp(path, PKGD_SANDBOX_DIR, n) &&
+           (path[n] == '/' || path[n] == '\0');
+}
+
+/* Append `s` as a TOML string. */
+static size_t put_string(char *buf, size_t n, const char *s)
+{
+    buf[n++] = '"';
+    for (; *s; s++) {
+        if (*s == '"' || *s == '\\')
+            buf[n++] = '\\';
+        buf[n++] = *s;
+    }
+    buf[n++] = '"';
+    return n;
+}
+
+/* Keys of the manifest's [sandbox] table copied into the profile */
+static int profile_key(const char *key, size_t len)
+{
+    static const char *keys[] = { "read", "write", "services", "network" };
+
+    for (size_t i = 0; i < sizeof(keys) / sizeof(keys[0]); i++)
+        if (strlen(keys[i]) == len && !memcmp(key, keys[i], len))
+            return 1;
+    return 0;
+}
+
+/*
+ * Build the sandbox profile of `vpk`: its executables as `programs`, and
+ * the grants of its manifest's [sandbox] table. The manifest cannot pick
+ * `programs`. Sets *out to NULL if the package ships no executables.
+ */
+static int make_profile(const pkgd_vpk_t *vpk, char **out, size_t *len)
+{
+    const char *p = vpk->manifest_text, *end = p + vpk->manifest_len;
+    size_t pos = 0, nexec = 0;
+    pkgd_file_t file;
+    const uint8_t *data;
+    int in_sandbox = 0;
+    int ret;
+
+    *out = NULL;
+    while ((ret = vpk_next_file(vpk, &pos, &file, &data)) > 0)
+        if (file.mode & 0111)
+            nexec++;
+    if (ret < 0 || nexec == 0)
+        return ret;
+
+    size_t cap = 256 + nexec * (2 * PKGD_MAX_PATH + 4) + vpk->manifest_len;
+    char *buf = malloc(cap);
+    if (!buf)
+        return -ENOMEM;
+
+    size_t n = (size_t)snprintf(buf, cap,
+                                "# Generated by pkgd for %s %s\n"
+                                "[sandbox]\nprograms = [",
+                                vpk->manifest.name, vpk->manifest.version);
+    for (pos = 0; vpk_next_file(vpk, &pos, &file, &data) > 0;) {
+        if (!(file.mode & 0111))
+            continue;
+        if (buf[n - 1] != '[') {
+            buf[n++] = ',';
+            buf[n++] = ' ';
+        }
+        n = put_string(buf, n, file.path);
+    }
+    buf[n++] = ']';
+    buf[n++] = '\n';
+
+    while (p < end) {
+        const char *eol = memchr(p, '\n', (size_t)(end - p));
+        if (!eol)
+            eol = end;
+        while (p < eol && (*p == ' ' || *p == '\t'))
+            p++;
+
+        if (p < eol && *p == '[') {
+            in_sandbox = (size_t)(eol - p) >= 9 && !memcmp(p, "[sandbox]", 9);
+        } else if (in_sandbox && p < eol && *p != '#') {
+            const char *key = p;
+            while (p < eol && *p != '=' && *p != ' ' && *p != '\t')
+                p++;
+            if (profile_key(key, (size_t)(p - key))) {
+                memcpy(buf + n, key, (size_t)(eol - key));
+                n += (size_t)(eol - key);
+                buf[n++] = '\n';
+            }
+        }
+        p = eol + 1;
+    }
+
+    *out = buf;
+    *len = n;
+    return 0;
+}
+
+/* Write the sandbox profile of `vpk` and add it to `rec`. */
+static int place_profile(const pkgd_vpk_t *vpk, pkgd_record_t *rec)
+{
+    char owner[PKGD_MAX_NAME];
+    pkgd_file_t f = { 0 };
+    char *text;
+    size_t len;
+    int ret;
+
+    if ((ret = make_profile(vpk, &text, &len)) < 0 || !text)
+        return ret;
+
+    snprintf(f.path, sizeof(f.path), "%s/%s.toml", PKGD_SANDBOX_DIR,
+             vpk->manifest.name);
+    f.size = len;
+    f.mode = 0644;
+    f.checksum = pkgd_checksum((const uint8_t *)text, len);
+
+    if (find_owner(f.path, vpk->manifest.name, owner)) {
+        printf("pkgd: %s: %s is owned by %s\n", vpk->manifest.name, f.path,
+               owner);
+        ret = -EEXIST;
+    } else if ((ret = place_file(&f, (const uint8_t *)text)) == 0) {
+        rec->files[rec->nfiles++] = f;
+        printf("pkgd: %s: sandboxed by %s\n", vpk->manifest.name, f.path);
+    }
+    free(text);
+    return ret;
+}
+
+/* ========================================================================= */
+/* Install / upgrade                                                         */
+/* ========================================================================= */
+
+/*
+ * Install the package named by `spec` (path, file:// URL or repository
+ * name), installing missing dependencies first. With `upgrade`, `spec` must
+ * name an installed package, which is replaced if the repository holds a
+ * newer version. Returns the number of packages installed or upgraded.
+ */
+int txn_install(const char *spec, int upgrade, int depth)
+{
+    char path[PKGD_MAX_PATH];
+    char owner[PKGD_MAX_NAME];
+    pkgd_record_t old = { 0 };
+    pkgd_record_t rec = { 0 };
+    pkgd_vpk_t vpk;
+    const uint8_t *kernel = NULL;
+    uint64_t kernel_size = 0;
+    int count = 0;
+    int ret;
+
+    if (upgrade && (ret = db_load(spec, &old)) < 0) {
+        printf("pkgd: %s is not installed\n", spec);
+        return ret;
+    }
+
+    if ((ret = fetch_package(spec, path, sizeof(path))) < 0) {
+        printf("pkgd: cannot fetch %s (error %d)\n", spec, -ret);
+        db_free(&old);
+        return ret;
+    }
+    if ((ret = vpk_load(path, &vpk)) < 0) {
+        printf("pkgd: %s is not a valid package (error %d)\n", path, -ret);
+        db_free(&old);
+        return ret;
+    }
+
+    const pkgd_manifest_t *m = &vpk.manifest;
+    if (upgrade) {
+        if (strcmp(m->name, old.manifest.name) != 0) {
+            ret = -EPROTO;
+            goto out;
+        }
+        if (pkgd_version_cmp(m->version, old.manifest.version) <= 0) {
+            ret = 0;
+            goto out;
+        }
+    } else if (db_is_installed(m->name)) {
+        printf("pkgd: %s is already installed\n", m->name);
+        ret = 0;
+        goto out;
+    }
+
+    for (int i = 0; i < m->ndeps; i++) {
+        if (db_is_installed(m->depends[i]))
+            continue;
+        if (depth >= PKGD_MAX_DEPTH) {
+            printf("pkgd: dependency chain too deep at %s\n", m->depends[i]);
+            ret = -ELOOP;
+            goto out;
+        }
+        printf("pkgd: %s requires %s\n", m->name, m->depends[i]);
+        ret = txn_install(m->depends[i], 0, depth + 1);
+        if (ret < 0)
+            goto out;
+        count += ret;
+    }
+
+    rec.manifest = *m;
+    rec.files = calloc(PKGD_MAX_FILES, sizeof(pkgd_file_t));
+    if (!rec.files) {
+        ret = -ENOMEM;
+        goto out;
+    }
+
+    if ((ret = journal_begin()) < 0)
+        goto out;
+
+    /* Confine the package's programs before they are installed */
+    int sandboxed = vpk.trust < PKGD_TRUST_CORE;
+    if (sandboxed)
+        ret = place_profile(&vpk, &rec);
+
+    size_t pos = 0;
+    pkgd_file_t file;
+    const uint8_t *data;
+    while (ret == 0 && (ret = vpk_next_file(&vpk, &pos, &file, &data)) > 0) {
+        if (rec.nfiles == PKGD_MAX_FILES) {
+            ret = -E2BIG;
+            break;
+        }
+        if (sandboxed && in_sandbox_dir(file.path)) {
+            printf("pkgd: %s: only core packages may install %s\n", m->name,
+                   file.path);
+            ret = -EPERM;
+            break;
+        }
+        if (find_owner(file.path, m->name, owner)) {
+            printf("pkgd: %s: %s is owned by %s\n", m->name, file.path, owner);
+            ret = -EEXIST;
+            break;
+        }
+        if ((ret = place_file(&file, data)) < 0) {
+            printf("pkgd: %s: cannot write %s (error %d)\n", m->name,
+                   file.path, -ret);
+            break;
+        }
+        rec.files[rec.nfiles++] = file;
+        if (strcmp(file.path, PKGD_KERNEL_PATH) == 0) {
+            kernel = data;
+            kernel_size = file.size;
+        }
+    }
+
+    /* Files the new version no longer ships */
+    for (int i = 0; ret == 0 && i < old.nfiles; i++)
+        if (!has_file(&rec, old.files[i].path) &&
+            access(old.files[i].path, F_OK) == 0)
+            ret = journal_backup(old.files[i].path);
+
+    if (ret == 0)
+        ret = journal_record(m->name);
+    if (ret == 0)
+        ret = db_save(&rec);
+    if (ret == 0 && kernel)
+        ret = stage_kernel(m->name, kernel, kernel_size);
+
+    if (ret < 0) {
+        printf("pkgd: %s: rolling back\n", m->name);
+        journal_undo();
+        goto out;
+    }
+    journal_commit();
+
+    if (upgrade)
+        printf("pkgd: upgraded %s %s -> %s\n", m->name, old.manifest.version,
+               m->version);
+    else
+        printf("pkgd: installed %s %s (%d files, signer %s)\n", m->name,
+               m->version, rec.nfiles,
+               vpk.trust >= 0 && vpk.trust <= 3 ? trust_names[vpk.trust]
+                                                 : "unverified");
+    ret = count + 1;
+
+out:
+    db_free(&rec);
+    db_free(&old);
+    vpk_free(&vpk);
+    return ret;
+}
+
+/* ========================================================================= */
+/* Remove                                                                    */
+/* ========================================================================= */
+
+int txn_remove(const char *name)
+{
+    static char names[PKGD_MAX_PACKAGES][PKGD_MAX_NAME];
+    pkgd_record_t rec;
+    int ret;
+
+    if ((ret = db_load(name, &rec)) < 0) {
+        printf("pkgd: %s is not installed\n", name);
+        return ret;
+    }
+
+    /* Refuse to break installed dependents */
+    int n = db_names(names, PKGD_MAX_PACKAGES);
+    for (int i = 0; i < n; i++) {
+        pkgd_record_t other;
+        if (!strcmp(names[i], name) || db_load(names[i], &other) < 0)
+            continue;
+        for (int j = 0; j < other.manifest.ndeps; j++) {
+            if (!strcmp(other.manifest.depends[j], name)) {
+                printf("pkgd: %s is required by %s\n", name, names[i]);
+                db_free(&other);
+                db_free(&rec);
+                return -EBUSY;
+            }
+        }
+        db_free(&other);
+    }
+
+    if ((ret = journal_begin()) < 0) {
+        db_free(&rec);
+        return ret;
+    }
+
+    /* Newest first, so a sandbox profile goes after the programs it covers */
+    for (int i = rec.nfiles - 1; ret == 0 && i >= 0; i--)
+        if (access(rec.files[i].path, F_OK) == 0)
+            ret = journal_backup(rec.files[i].path);
+    if (ret == 0)
+        ret = journal_record(name);
+
+    if (ret < 0) {
+        printf("pkgd: %s: rolling back (error %d)\n", name, -ret);
+        journal_undo();
+    } else {
+        journal_commit();
+        printf("pkgd: removed %s %s\n", name, rec.manifest.version);
+    }
+
+    db_free(&rec);
+    return ret;
+}
+
+/* ========================================================================= */
+/* Verify                                                                    */
+/* ========================================================================= */
+
+/* Check installed files against the record. Returns the number of
+ * missing or modified files. */
+int txn_verify(const char *name)
+{
+    pkgd_record_t rec;
+    int bad = 0;
+    int ret;
+
+    if ((ret = db_load(name, &rec)) < 0) {
+        printf("pkgd: %s is not installed\n", name);
+        return ret;
+    }
+
+    for (int i = 0; i < rec.nfiles; i++) {
+        const pkgd_file_t *f = &rec.files[i];
+        uint8_t *data;
+        size_t len;
+
+        if (pkgd_read_file(f->path, &data, &len) < 0) {
+            printf("  MISSING  %s\n", f->path);
+            bad++;
+            continue;
+        }
+        if (len != f->size || pkgd_checksum(data, len) != f->checksum) {
+            printf("  MODIFIED %s\n", f->path);
+            bad++;
+        }
+        free(data);
+    }
+
+    printf("pkgd: %s %s: %d files, %s\n", name, rec.manifest.version,
+           rec.nfiles, bad ? "FAILED" : "OK");
+    db_free(&rec);
+    return bad;
+}
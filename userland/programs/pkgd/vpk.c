@@ -0,0 +1,368 @@
+/*
+ * VeridianOS Package Service -- vpk.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * .vpk archive loading: framing checks, signature verification (done by
+ * the kernel via SYS_PKG_VERIFY so the trusted key ring stays there),
+ * archive decompression and the TOML manifest.
+ *
+ * Layout (little-endian):
+ *   [4]  "VPK1"          [2] format version   [1] compression  [1] reserved
+ *   [4]  manifest size   [4] archive size     [32] signer public key
+ *   manifest (TOML), archive (compressed file table), [64] signature
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <veridian/syscall.h>
+
+#include "pkgd.h"
+
+#define LZ4_MAGIC       0x184D2204u
+#define LZ4_HEADER_SIZE 15
+#define LZ4_MIN_MATCH   4
+
+static uint16_t get16(const uint8_t *p) { return (uint16_t)(p[0] | (p[1] << 8)); }
+
+static uint32_t get32(const uint8_t *p)
+{
+    return (uint32_t)p[0] | ((uint32_t)p[1] << 8) | ((uint32_t)p[2] << 16) |
+           ((uint32_t)p[3] << 24);
+}
+
+static uint64_t get64(const uint8_t *p)
+{
+    return (uint64_t)get32(p) | ((uint64_t)get32(p + 4) << 32);
+}
+
+/* ========================================================================= */
+/* LZ4 frame decoder (mirrors kernel/src/pkg/format/compression.rs)          */
+/* ========================================================================= */
+
+static int lz4_block(const uint8_t *in, size_t len, uint8_t *out, size_t cap,
+                     size_t *used)
+{
+    size_t pos = 0, o = *used;
+
+    while (pos < len) {
+        uint8_t token = in[pos++];
+
+        size_t lit = token >> 4;
+        if (lit == 15) {
+            while (pos < len) {
+                uint8_t b = in[pos++];
+                lit += b;
+                if (b != 255)
+                    break;
+            }
+        }
+        if (pos + lit > len || o + lit > cap)
+            return -EPROTO;
+        memcpy(out + o, in + pos, lit);
+        pos += lit;
+        o += lit;
+
+        if (pos >= len)
+            break;
+
+        if (pos + 2 > len)
+            return -EPROTO;
+        size_t offset = get16(in + pos);
+        pos += 2;
+        if (offset == 0 || offset > o)
+            return -EPROTO;
+
+        size_t match = (size_t)(token & 0x0F) + LZ4_MIN_MATCH;
+        if ((token & 0x0F) == 15) {
+            while (pos < len) {
+                uint8_t b = in[pos++];
+                match += b;
+                if (b != 255)
+                    break;
+            }
+        }
+        if (o + match > cap)
+            return -EPROTO;
+        /* Byte-wise so overlapping matches replicate correctly */
+        for (size_t i = 0; i < match; i++, o++)
+            out[o] = out[o - offset];
+    }
+
+    *used = o;
+    return 0;
+}
+
+static int lz4_decompress(const uint8_t *in, size_t len, uint8_t **out,
+                          size_t *out_len)
+{
+    if (len < LZ4_HEADER_SIZE || get32(in) != LZ4_MAGIC)
+        return -EPROTO;
+
+    uint64_t size = get64(in + 6);
+    if (size > 256u * 1024 * 1024)
+        return -EFBIG;
+
+    uint8_t *buf = malloc(size ? (size_t)size : 1);
+    if (!buf)
+        return -ENOMEM;
+
+    size_t pos = LZ4_HEADER_SIZE, used = 0;
+    while (pos + 4 <= len) {
+        uint32_t raw = get32(in + pos);
+        pos += 4;
+        if (raw == 0)
+            break;
+
+        size_t block = raw & 0x7FFFFFFFu;
+        if (pos + block > len)
+            goto bad;
+        if (raw & 0x80000000u) {
+            if (used + block > size)
+                goto bad;
+            memcpy(buf + used, in + pos, block);
+            used += block;
+        } else if (lz4_block(in + pos, block, buf, (size_t)size, &used) < 0) {
+            goto bad;
+        }
+        pos += block;
+    }
+    if (used != size)
+        goto bad;
+
+    *out = buf;
+    *out_len = used;
+    return 0;
+
+bad:
+    free(buf);
+    return -EPROTO;
+}
+
+/* ========================================================================= */
+/* Manifest                                                                  */
+/* ========================================================================= */
+
+/* Copy a quoted TOML string starting at `p` into `dst`. Returns the position
+ * after the closing quote, or NULL. */
+static const char *parse_string(const char *p, const char *end, char *dst,
+                                size_t cap)
+{
+    size_t n = 0;
+    if (p >= end || *p != '"')
+        return NULL;
+    for (p++; p < end && *p != '"'; p++) {
+        if (*p == '\\' && p + 1 < end)
+            p++;
+        if (n + 1 < cap)
+            dst[n++] = *p;
+    }
+    dst[n] = '\0';
+    return p < end ? p + 1 : NULL;
+}
+
+static int valid_name(const char *name)
+{
+    if (!*name || *name == '.')
+        return 0;
+    for (const char *p = name; *p; p++) {
+        char c = *p;
+        if (!((c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') ||
+              (c >= '0' && c <= '9') || c == '-' || c == '_' || c == '.'))
+            return 0;
+    }
+    return 1;
+}
+
+/* Parse the keys of the [package] table; other tables are ignored. */
+int manifest_parse(const char *text, size_t len, pkgd_manifest_t *m)
+{
+    const char *p = text, *end = text + len;
+    int in_package = 0;
+
+    memset(m, 0, sizeof(*m));
+
+    while (p < end) {
+        const char *eol = memchr(p, '\n', (size_t)(end - p));
+        if (!eol)
+            eol = end;
+
+        while (p < eol && (*p == ' ' || *p == '\t'))
+            p++;
+
+        if (p < eol && *p == '[') {
+            in_package = (size_t)(eol - p) >= 9 && !memcmp(p, "[package]", 9);
+        } else if (in_package && p < eol && *p != '#') {
+            const char *key = p;
+            while (p < eol && *p != '=' && *p != ' ' && *p != '\t')
+                p++;
+            size_t klen = (size_t)(p - key);
+            while (p < eol && (*p == ' ' || *p == '\t' || *p == '='))
+                p++;
+
+            if (klen == 4 && !memcmp(key, "name", 4)) {
+                parse_string(p, eol, m->name, sizeof(m->name));
+            } else if (klen == 7 && !memcmp(key, "version", 7)) {
+                parse_string(p, eol, m->version, sizeof(m->version));
+            } else if (klen == 11 && !memcmp(key, "description", 11)) {
+                parse_string(p, eol, m->description, sizeof(m->description));
+            } else if (klen == 7 && !memcmp(key, "license", 7)) {
+                parse_string(p, eol, m->license, sizeof(m->license));
+            } else if (klen == 7 && !memcmp(key, "depends", 7) && p < eol &&
+                       *p == '[') {
+                for (p++; p < eol && *p != ']';) {
+                    if (*p == '"' && m->ndeps < PKGD_MAX_DEPS) {
+                        p = parse_string(p, eol, m->depends[m->ndeps],
+                                         PKGD_MAX_NAME);
+                        if (!p)
+                            return -EPROTO;
+                        m->ndeps++;
+                    } else {
+                        p++;
+                    }
+                }
+            }
+        }
+        p = eol + 1;
+    }
+
+    if (!valid_name(m->name) || !m->version[0])
+        return -EPROTO;
+    for (int i = 0; i < m->ndeps; i++)
+        if (!valid_name(m->depends[i]))
+            return -EPROTO;
+    return 0;
+}
+
+/* ========================================================================= */
+/* Archive loading                                                           */
+/* ========================================================================= */
+
+int vpk_load(const char *path, pkgd_vpk_t *vpk)
+{
+    int ret;
+
+    memset(vpk, 0, sizeof(*vpk));
+    ret = pkgd_read_file(path, &vpk->data, &vpk->len);
+    if (ret < 0)
+        return ret;
+
+    const uint8_t *d = vpk->data;
+    ret = -EPROTO;
+    if (vpk->len < VPK_HEADER_SIZE + VPK_SIGNATURE_SIZE ||
+        memcmp(d, VPK_MAGIC, 4) != 0 || get16(d + 4) != VPK_VERSION)
+        goto fail;
+
+    size_t manifest_size = get32(d + 8);
+    size_t archive_size = get32(d + 12);
+    if (manifest_size > vpk->len || archive_size > vpk->len ||
+        VPK_HEADER_SIZE + manifest_size + archive_size + VPK_SIGNATURE_SIZE !=
+            vpk->len)
+        goto fail;
+
+    /* Signature and trust policy are checked by the kernel */
+    long trust = veridian_syscall2(SYS_PKG_VERIFY, d, vpk->len);
+    if (trust < 0) {
+        printf("pkgd: %s: signature rejected (error %ld)\n", path, -trust);
+        ret = (int)trust;
+        goto fail;
+    }
+    vpk->trust = (int)trust;
+
+    const char *manifest = (const char *)d + VPK_HEADER_SIZE;
+    vpk->manifest_text = manifest;
+    vpk->manifest_len = manifest_size;
+    ret = manifest_parse(manifest, manifest_size, &vpk->manifest);
+    if (ret < 0)
+        goto fail;
+
+    const uint8_t *archive = d + VPK_HEADER_SIZE + manifest_size;
+    switch (d[6]) {
+    case VPK_COMPRESS_NONE:
+        vpk->table = malloc(archive_size ? archive_size : 1);
+        if (!vpk->table) {
+            ret = -ENOMEM;
+            goto fail;
+        }
+        memcpy(vpk->table, archive, archive_size);
+        vpk->table_len = archive_size;
+        break;
+    case VPK_COMPRESS_LZ4:
+        ret = lz4_decompress(archive, archive_size, &vpk->table,
+                             &vpk->table_len);
+        if (ret < 0)
+            goto fail;
+        break;
+    default:
+        ret = -ENOTSUP;
+        goto fail;
+    }
+
+    if (vpk->table_len < 4) {
+        ret = -EPROTO;
+        goto fail;
+    }
+    return 0;
+
+fail:
+    vpk_free(vpk);
+    return ret;
+}
+
+void vpk_free(pkgd_vpk_t *vpk)
+{
+    free(vpk->data);
+    free(vpk->table);
+    vpk->data = NULL;
+    vpk->table = NULL;
+}
+
+static int valid_path(const char *path)
+{
+    if (path[0] != '/' || path[1] == '\0')
+        return 0;
+    for (const char *p = path; (p = strstr(p, "..")) != NULL; p += 2)
+        if (p[-1] == '/' && (p[2] == '/' || p[2] == '\0'))
+            return 0;
+    return 1;
+}
+
+/*
+ * Iterate the file table. `*pos` starts at 0. Returns 1 with `file` and
+ * `data` filled in, 0 at the end of the table, or a negated errno.
+ */
+int vpk_next_file(const pkgd_vpk_t *vpk, size_t *pos, pkgd_file_t *file,
+                  const uint8_t **data)
+{
+    const uint8_t *t = vpk->table;
+    size_t len = vpk->table_len;
+    size_t p = *pos ? *pos : 4;
+
+    if (p >= len)
+        return 0;
+    if (p + 2 > len)
+        return -EPROTO;
+
+    size_t path_len = get16(t + p);
+    p += 2;
+    if (path_len >= sizeof(file->path) || p + path_len + 12 > len)
+        return -EPROTO;
+    memcpy(file->path, t + p, path_len);
+    file->path[path_len] = '\0';
+    p += path_len;
+
+    uint64_t size = get64(t + p);
+    file->mode = get32(t + p + 8);
+    p += 12;
+    if (size > len - p || !valid_path(file->path))
+        return -EPROTO;
+
+    file->size = size;
+    file->checksum = pkgd_checksum(t + p, (size_t)size);
+    *data = t + p;
+    *pos = p + (size_t)size;
+    return 1;
+}
@@ -0,0 +1,647 @@
+/*
+ * sysmon -- VeridianOS system monitor
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Desktop window listing every process with its state, nice value, CPU
+ * share, resident memory and thread count, above live CPU and memory
+ * graphs. Everything is read from procfs once a second: /proc/stat and
+ * /proc/meminfo for the graphs, /proc/<pid>/stat for the table.
+ *
+ * Usage:
+ *   sysmon
+ *
+ * Click a column header to sort by it, click again to reverse. Click a row
+ * or use Up/Down to select a process, then End (SIGTERM), Kill (SIGKILL),
+ * or Nice -/+ to move it to the next higher or lower priority class.
+ * Keys: t end, k kill, - and + renice, 1-7 sort, q quit. Signalling or
+ * renicing another user's process needs root.
+ */
+
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdarg.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <time.h>
+#include <unistd.h>
+#include <veridian/window.h>
+
+#include "font8x16.h"
+
+#define WIDTH           640
+#define HEIGHT          440
+#define MAX_PROCS       256
+#define SAMPLE_MS       1000
+
+/* Layout */
+#define SUMMARY_Y       4
+#define GRAPH_Y         26
+#define GRAPH_H         80
+#define GRAPH_W         308
+#define HEADER_Y        114
+#define ROW_H           18
+#define ROWS_Y          (HEADER_Y + ROW_H + 2)
+#define BAR_Y           (HEIGHT - 28)
+#define VISIBLE_ROWS    ((BAR_Y - ROWS_Y) / ROW_H)
+#define HISTORY         (GRAPH_W / 2)   /* one sample per 2 pixels */
+
+/* Colors (0x00RRGGBB) */
+#define C_BG            0x1E1E2E
+#define C_PANEL         0x28283A
+#define C_GRID          0x3A3A50
+#define C_TEXT          0xDCDCE6
+#define C_DIM           0x8C8CA0
+#define C_HEADER        0x323248
+#define C_SELECTED      0x3C5A96
+#define C_CPU           0x50C878
+#define C_MEM           0x5A9BE6
+#define C_BUTTON        0x3C3C55
+#define C_ERROR         0xE66464
+
+enum column { COL_PID, COL_NAME, COL_STATE, COL_NICE, COL_CPU, COL_MEM, COL_THREADS, NCOLS };
+
+static const struct {
+    const char *title;
+    int x, w;
+    int right;          /* right-aligned */
+    int desc;           /* sort descending first */
+} columns[NCOLS] = {
+    { "PID",     8,   56, 1, 0 },
+    { "Name",    72,  192, 0, 0 },
+    { "State",   264, 56, 0, 0 },
+    { "Nice",    320, 48, 1, 0 },
+    { "CPU%",    368, 72, 1, 1 },
+    { "Memory",  440, 104, 1, 1 },
+    { "Threads", 544, 88, 1, 1 },
+};
+
+enum action { ACT_END, ACT_KILL, ACT_NICE_DOWN, ACT_NICE_UP, NACTIONS };
+
+static const struct {
+    const char *label;
+    int x, w;
+} buttons[NACTIONS] = {
+    { "End",    8,   56 },
+    { "Kill",   72,  56 },
+    { "Nice -", 136, 64 },
+    { "Nice +", 208, 64 },
+};
+
+/* Priority classes reachable with setpriority(): system, normal, low. */
+static const int nice_steps[] = { -20, 0, 10 };
+#define NICE_STEPS ((int)(sizeof(nice_steps) / sizeof(nice_steps[0])))
+
+struct proc {
+    long pid;
+    char name[32];
+    char state;
+    int nice;
+    int threads;
+    unsigned long ticks;        /* user + system CPU time, clock ticks */
+    unsigned long cpu;          /* CPU share over the last sample, 0.1% */
+    unsigned long rss_kb;
+};
+
+static uint32_t fb[WIDTH * HEIGHT];
+
+static struct proc procs[MAX_PROCS], prev[MAX_PROCS];
+static int nprocs, nprev;
+
+static unsigned long cpu_hist[HISTORY], mem_hist[HISTORY];  /* 0.1% */
+static int nhist;
+static unsigned long prev_total, prev_idle;
+static unsigned long mem_total_kb, mem_used_kb;
+
+static int sort_col = COL_CPU, sort_desc = 1;
+static long sel_pid = -1;
+static int top_row;
+static char status_msg[96];
+static int status_error;
+
+/* ------------------------------------------------------------------------- */
+/* procfs                                                                    */
+/* ------------------------------------------------------------------------- */
+
+static int read_file(const char *path, char *buf, size_t size)
+{
+    int fd = open(path, O_RDONLY);
+    ssize_t n, total = 0;
+
+    if (fd < 0)
+        return -1;
+    while (total < (ssize_t)size - 1 && (n = read(fd, buf + total, size - 1 - total)) > 0)
+        total += n;
+    close(fd);
+    buf[total] = '\0';
+    return (int)total;
+}
+
+/* Parse /proc/<pid>/stat: "pid (comm) S ppid ..." */
+static int read_proc(long pid, struct proc *p)
+{
+    char path[32], buf[512], *s, *end;
+    unsigned long field[24];
+    size_t len;
+    int i;
+
+    snprintf(path, sizeof(path), "/proc/%ld/stat", pid);
+    if (read_file(path, buf, sizeof(buf)) <= 0)
+        return -1;
+    s = strchr(buf, '(');
+    end = strrchr(buf, ')');
+    if (!s || !end || end < s || end[1] != ' ')
+        return -1;
+
+    len = (size_t)(end - s - 1);
+    if (len >= sizeof(p->name))
+        len = sizeof(p->name) - 1;
+    memcpy(p->name, s + 1, len);
+    p->name[len] = '\0';
+    p->pid = pid;
+    p->state = end[2];
+
+    /* Fields 4 onwards (ppid ...) are numbers; nice may be negative */
+    s = end + 3;
+    for (i = 3; i < 24; i++) {
+        field[i] = (unsigned long)strtol(s, &end, 10);
+        if (end == s)
+            return -1;
+        s = end;
+    }
+    p->ticks = field[13] + field[14];
+    p->nice = (int)(long)field[18];
+    p->threads = (int)field[19];
+    p->rss_kb = field[23] * 4;
+    p->cpu = 0;
+    return 0;
+}
+
+static unsigned long meminfo_field(const char *buf, const char *name)
+{
+    const char *s = strstr(buf, name);
+    return s ? strtoul(s + strlen(name), NULL, 10) : 0;
+}
+
+static const struct proc *find_prev(long pid)
+{
+    int i;
+
+    for (i = 0; i < nprev; i++)
+        if (prev[i].pid == pid)
+            return &prev[i];
+    return NULL;
+}
+
+static void push_history(unsigned long *hist, unsigned long value)
+{
+    if (nhist == HISTORY)
+        memmove(hist, hist + 1, (HISTORY - 1) * sizeof(*hist));
+    hist[nhist == HISTORY ? HISTORY - 1 : nhist] = value;
+}
+
+static void sample(void)
+{
+    char buf[512];
+    unsigned long user = 0, nice = 0, sys = 0, idle = 0, total, dtotal = 0;
+    struct dirent *de;
+    DIR *dir;
+    int i;
+
+    memcpy(prev, procs, sizeof(procs[0]) * (size_t)nprocs);
+    nprev = nprocs;
+    nprocs = 0;
+
+    if (read_file("/proc/stat", buf, sizeof(buf)) > 0)
+        sscanf(buf, "cpu %lu %lu %lu %lu", &user, &nice, &sys, &idle);
+    total = user + nice + sys + idle;
+    if (prev_total && total > prev_total)
+        dtotal = total - prev_total;
+
+    dir = opendir("/proc");
+    if (dir) {
+        while ((de = readdir(dir)) != NULL && nprocs < MAX_PROCS) {
+            char *end;
+            long pid = strtol(de->d_name, &end, 10);
+            if (end == de->d_name || *end != '\0')
+                continue;
+            if (read_proc(pid, &procs[nprocs]) == 0)
+                nprocs++;
+        }
+        closedir(dir);
+    }
+
+    for (i = 0; i < nprocs; i++) {
+        const struct proc *old = find_prev(procs[i].pid);
+        if (dtotal && old && procs[i].ticks >= old->ticks)
+            procs[i].cpu = (procs[i].ticks - old->ticks) * 1000 / dtotal;
+    }
+
+    if (read_file("/proc/meminfo", buf, sizeof(buf)) > 0) {
+        mem_total_kb = meminfo_field(buf, "MemTotal:");
+        mem_used_kb = mem_total_kb - meminfo_field(buf, "MemAvailable:");
+    }
+
+    if (dtotal) {
+        unsigned long didle = idle >= prev_idle ? idle - prev_idle : 0;
+        unsigned long busy = didle < dtotal ? dtotal - didle : 0;
+        push_history(cpu_hist, busy * 1000 / dtotal);
+        push_history(mem_hist, mem_total_kb ? mem_used_kb * 1000 / mem_total_kb : 0);
+        if (nhist < HISTORY)
+            nhist++;
+    }
+    prev_total = total;
+    prev_idle = idle;
+}
+
+/* ------------------------------------------------------------------------- */
+/* Sorting and selection                                                     */
+/* ------------------------------------------------------------------------- */
+
+static int compare(const void *a, const void *b)
+{
+    const struct proc *p = a, *q = b;
+    long d;
+
+    switch (sort_col) {
+    case COL_NAME:    d = strcmp(p->name, q->name); break;
+    case COL_STATE:   d = p->state - q->state; break;
+    case COL_NICE:    d = p->nice - q->nice; break;
+    case COL_CPU:     d = (long)p->cpu - (long)q->cpu; break;
+    case COL_MEM:     d = (long)p->rss_kb - (long)q->rss_kb; break;
+    case COL_THREADS: d = p->threads - q->threads; break;
+    default:          d = 0; break;
+    }
+    if (d == 0)
+        d = p->pid - q->pid;
+    if (sort_desc)
+        d = -d;
+    return d < 0 ? -1 : d > 0;
+}
+
+static int selected_row(void)
+{
+    int i;
+
+    for (i = 0; i < nprocs; i++)
+        if (procs[i].pid == sel_pid)
+            return i;
+    return -1;
+}
+
+static void scroll_to(int row)
+{
+    if (row < top_row)
+        top_row = row;
+    else if (row >= top_row + VISIBLE_ROWS)
+        top_row = row - VISIBLE_ROWS + 1;
+    if (top_row > nprocs - VISIBLE_ROWS)
+        top_row = nprocs - VISIBLE_ROWS;
+    if (top_row < 0)
+        top_row = 0;
+}
+
+static void select_row(int row)
+{
+    if (nprocs == 0)
+        return;
+    if (row < 0)
+        row = 0;
+    if (row >= nprocs)
+        row = nprocs - 1;
+    sel_pid = procs[row].pid;
+    scroll_to(row);
+}
+
+static void sort_by(int col)
+{
+    if (col == sort_col)
+        sort_desc = !sort_desc;
+    else {
+        sort_col = col;
+        sort_desc = columns[col].desc;
+    }
+    qsort(procs, (size_t)nprocs, sizeof(procs[0]), compare);
+}
+
+/* ------------------------------------------------------------------------- */
+/* Actions                                                                   */
+/* ------------------------------------------------------------------------- */
+
+static void set_status(int error, const char *fmt, ...)
+{
+    va_list ap;
+
+    va_start(ap, fmt);
+    vsnprintf(status_msg, sizeof(status_msg), fmt, ap);
+    va_end(ap);
+    status_error = error;
+}
+
+static void act(enum action a)
+{
+    int row = selected_row();
+    const struct proc *p;
+    int i, nice;
+
+    if (row < 0) {
+        set_status(1, "no process selected");
+        return;
+    }
+    p = &procs[row];
+    if (p->pid == 1 && (a == ACT_END || a == ACT_KILL)) {
+        set_status(1, "refusing to signal init");
+        return;
+    }
+
+    switch (a) {
+    case ACT_END:
+    case ACT_KILL:
+        if (kill((pid_t)p->pid, a == ACT_KILL ? SIGKILL : SIGTERM) < 0)
+            set_status(1, "signal %ld: %s", p->pid, strerror(errno));
+        else
+            set_status(0, "sent %s to %ld", a == ACT_KILL ? "SIGKILL" : "SIGTERM", p->pid);
+        break;
+    case ACT_NICE_DOWN:
+    case ACT_NICE_UP:
+        for (i = NICE_STEPS - 1; i > 0 && nice_steps[i] > p->nice; i--)
+            ;
+        i += a == ACT_NICE_UP ? 1 : -1;
+        if (i < 0 || i >= NICE_STEPS) {
+            set_status(1, "%ld is already at the %s priority", p->pid,
+                       a == ACT_NICE_UP ? "lowest" : "highest");
+            return;
+        }
+        nice = nice_steps[i];
+        if (setpriority(PRIO_PROCESS, (id_t)p->pid, nice) < 0)
+            set_status(1, "renice %ld: %s", p->pid, strerror(errno));
+        else {
+            procs[row].nice = getpriority(PRIO_PROCESS, (id_t)p->pid);
+            set_status(0, "reniced %ld to %d", p->pid, procs[row].nice);
+        }
+        break;
+    default:
+        break;
+    }
+}
+
+/* ------------------------------------------------------------------------- */
+/* Drawing                                                                   */
+/* ------------------------------------------------------------------------- */
+
+static void fill(int x, int y, int w, int h, uint32_t color)
+{
+    int i, j;
+
+    if (x < 0) { w += x; x = 0; }
+    if (y < 0) { h += y; y = 0; }
+    if (x + w > WIDTH)
+        w = WIDTH - x;
+    if (y + h > HEIGHT)
+        h = HEIGHT - y;
+    for (j = 0; j < h; j++)
+        for (i = 0; i < w; i++)
+            fb[(y + j) * WIDTH + x + i] = color;
+}
+
+/* Draw at most max_w pixels of text; returns the width drawn. */
+static int text(int x, int y, const char *s, uint32_t color, int max_w)
+{
+    int start = x, row, bit;
+
+    for (; *s && x + FONT_WIDTH <= start + max_w && x + FONT_WIDTH <= WIDTH; s++) {
+        unsigned char c = (unsigned char)*s;
+        const unsigned char *glyph;
+        if (c < FONT_FIRST || c > FONT_LAST)
+            c = '?';
+        glyph = font8x16[c - FONT_FIRST];
+        for (row = 0; row < FONT_HEIGHT && y + row < HEIGHT; row++)
+            for (bit = 0; bit < FONT_WIDTH; bit++)
+                if (glyph[row] & (0x80 >> bit))
+                    fb[(y + row) * WIDTH + x + bit] = color;
+        x += FONT_WIDTH;
+    }
+    return x - start;
+}
+
+static void text_right(int x, int w, int y, const char *s, uint32_t color)
+{
+    int len = (int)strlen(s) * FONT_WIDTH;
+    text(len < w ? x + w - len - 4 : x, y, s, color, w);
+}
+
+static void format_tenths(char *buf, size_t size, unsigned long v)
+{
+    snprintf(buf, size, "%lu.%lu", v / 10, v % 10);
+}
+
+static void format_kb(char *buf, size_t size, unsigned long kb)
+{
+    if (kb >= 10 * 1024)
+        snprintf(buf, size, "%lu MB", kb / 1024);
+    else
+        snprintf(buf, size, "%lu KB", kb);
+}
+
+static void draw_graph(int x, const char *label, const unsigned long *hist, uint32_t color)
+{
+    char buf[48], pct[24];
+    int i, h;
+
+    fill(x, GRAPH_Y, GRAPH_W, GRAPH_H, C_PANEL);
+    for (i = 1; i < 4; i++)
+        fill(x, GRAPH_Y + GRAPH_H * i / 4, GRAPH_W, 1, C_GRID);
+    for (i = 0; i < nhist; i++) {
+        h = (int)(hist[i] * GRAPH_H / 1000);
+        fill(x + (HISTORY - nhist + i) * 2, GRAPH_Y + GRAPH_H - h, 2, h, color);
+    }
+    format_tenths(pct, sizeof(pct), nhist ? hist[nhist - 1] : 0);
+    snprintf(buf, sizeof(buf), "%s %s%%", label, pct);
+    text(x + 4, GRAPH_Y + 2, buf, C_TEXT, GRAPH_W);
+}
+
+static void draw_row(int y, const struct proc *p)
+{
+    char buf[32];
+
+    snprintf(buf, sizeof(buf), "%ld", p->pid);
+    text_right(columns[COL_PID].x, columns[COL_PID].w, y, buf, C_TEXT);
+    text(columns[COL_NAME].x, y, p->name, C_TEXT, columns[COL_NAME].w - 8);
+    snprintf(buf, sizeof(buf), "%c", p->state);
+    text(columns[COL_STATE].x, y, buf, C_TEXT, columns[COL_STATE].w);
+    snprintf(buf, sizeof(buf), "%d", p->nice);
+    text_right(columns[COL_NICE].x, columns[COL_NICE].w, y, buf, C_TEXT);
+    format_tenths(buf, sizeof(buf), p->cpu);
+    text_right(columns[COL_CPU].x, columns[COL_CPU].w, y, buf, C_TEXT);
+    format_kb(buf, sizeof(buf), p->rss_kb);
+    text_right(columns[COL_MEM].x, columns[COL_MEM].w, y, buf, C_TEXT);
+    snprintf(buf, sizeof(buf), "%d", p->threads);
+    text_right(columns[COL_THREADS].x, columns[COL_THREADS].w, y, buf, C_TEXT);
+}
+
+static void draw(void)
+{
+    char buf[96], used[16], total[16];
+    int i, y;
+
+    fill(0, 0, WIDTH, HEIGHT, C_BG);
+
+    format_kb(used, sizeof(used), mem_used_kb);
+    format_kb(total, sizeof(total), mem_total_kb);
+    snprintf(buf, sizeof(buf), "%d processes    memory %s of %s", nprocs, used, total);
+    text(8, SUMMARY_Y, buf, C_DIM, WIDTH - 16);
+
+    draw_graph(8, "CPU", cpu_hist, C_CPU);
+    draw_graph(8 + GRAPH_W + 8, "Memory", mem_hist, C_MEM);
+
+    fill(0, HEADER_Y, WIDTH, ROW_H, C_HEADER);
+    for (i = 0; i < NCOLS; i++) {
+        snprintf(buf, sizeof(buf), "%s%s", columns[i].title,
+                 i == sort_col ? (sort_desc ? " v" : " ^") : "");
+        if (columns[i].right)
+            text_right(columns[i].x, columns[i].w, HEADER_Y + 1, buf, C_TEXT);
+        else
+            text(columns[i].x, HEADER_Y + 1, buf, C_TEXT, columns[i].w);
+    }
+
+    for (i = top_row, y = ROWS_Y; i < nprocs && y + ROW_H <= BAR_Y; i++, y += ROW_H) {
+        if (procs[i].pid == sel_pid)
+            fill(0, y, WIDTH, ROW_H, C_SELECTED);
+        draw_row(y + 1, &procs[i]);
+    }
+
+    fill(0, BAR_Y, WIDTH, HEIGHT - BAR_Y, C_PANEL);
+    for (i = 0; i < NACTIONS; i++) {
+        fill(buttons[i].x, BAR_Y + 4, buttons[i].w, 20, C_BUTTON);
+        text(buttons[i].x + (buttons[i].w - (int)strlen(buttons[i].label) * FONT_WIDTH) / 2,
+             BAR_Y + 6, buttons[i].label, C_TEXT, buttons[i].w);
+    }
+    text(288, BAR_Y + 6, status_msg, status_error ? C_ERROR : C_DIM, WIDTH - 296);
+}
+
+/* ------------------------------------------------------------------------- */
+/* Input                                                                     */
+/* ------------------------------------------------------------------------- */
+
+static void click(int x, int y)
+{
+    int i;
+
+    if (y >= HEADER_Y && y < HEADER_Y + ROW_H) {
+        for (i = 0; i < NCOLS; i++)
+            if (x >= columns[i].x && x < columns[i].x + columns[i].w)
+                sort_by(i);
+    } else if (y >= ROWS_Y && y < BAR_Y) {
+        i = top_row + (y - ROWS_Y) / ROW_H;
+        if (i < nprocs)
+            sel_pid = procs[i].pid;
+    } else if (y >= BAR_Y + 4 && y < BAR_Y + 24) {
+        for (i = 0; i < NACTIONS; i++)
+            if (x >= buttons[i].x && x < buttons[i].x + buttons[i].w)
+                act((enum action)i);
+    }
+}
+
+/* Returns 0 when the window should close. */
+static int key(unsigned int scancode, int ch)
+{
+    switch (scancode) {
+    case 0x80: select_row(selected_row() - 1); return 1;    /* Up */
+    case 0x81: select_row(selected_row() + 1); return 1;    /* Down */
+    default: break;
+    }
+    switch (ch) {
+    case 'q': return 0;
+    case 't': act(ACT_END); break;
+    case 'k': act(ACT_KILL); break;
+    case '-': act(ACT_NICE_DOWN); break;
+    case '+': case '=': act(ACT_NICE_UP); break;
+    default:
+        if (ch >= '1' && ch < '1' + NCOLS)
+            sort_by(ch - '1');
+        break;
+    }
+    return 1;
+}
+
+static long now_ms(void)
+{
+    struct timespec ts;
+
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+int main(void)
+{
+    struct win_event events[32];
+    long win, n = 0, last = 0;
+    int running = 1, dirty = 1, i;
+
+    win = win_create(WIDTH, HEIGHT, "System Monitor");
+    if (win < 0) {
+        fprintf(stderr, "sysmon: cannot open window (%ld)%s\n", win,
+                win == -8 ? ": desktop not running" : "");
+        return 1;
+    }
+
+    while (running) {
+        if (now_ms() - last >= SAMPLE_MS) {
+            last = now_ms();
+            sample();
+            qsort(procs, (size_t)nprocs, sizeof(procs[0]), compare);
+            if (sel_pid < 0 || selected_row() < 0)
+                select_row(0);
+            scroll_to(selected_row());
+            dirty = 1;
+        }
+
+        while (running && (n = win_poll(win, events, 32)) > 0) {
+            for (i = 0; i < n && running; i++) {
+                switch (events[i].kind) {
+                case WIN_EVENT_CLOSE:
+                    running = 0;
+                    break;
+                case WIN_EVENT_KEY_PRESS:
+                    running = key(events[i].code, events[i].x);
+                    break;
+                case WIN_EVENT_BUTTON_PRESS:
+                    if (events[i].code == 0)
+                        click(events[i].x, events[i].y);
+                    break;
+                case WIN_EVENT_SCROLL:
+                    top_row += events[i].y > 0 ? -3 : 3;
+                    if (top_row > nprocs - VISIBLE_ROWS)
+                        top_row = nprocs - VISIBLE_ROWS;
+                    if (top_row < 0)
+                        top_row = 0;
+                    break;
+                default:
+                    continue;
+                }
+                dirty = 1;
+            }
+        }
+        if (n < 0) {
+            fprintf(stderr, "sysmon: window lost (%ld)\n", n);
+            return 1;
+        }
+
+        if (dirty && running) {
+            draw();
+            if (win_present(win, fb, sizeof(fb)) < 0)
+                return 1;
+            dirty = 0;
+        }
+        usleep(30000);
+    }
+
+    win_destroy(win);
+    return 0;
+}
@@ -0,0 +1,213 @@
+/*
+ * vctl -- control the services supervised by init
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Services are defined by the unit files in /etc/init and supervised by
+ * init (userland/init). Requests go to init over the "init" IPC endpoint
+ * (protocol: <veridian/init.h>); unit state is read from the table init
+ * publishes in /run/init/units.
+ *
+ * Usage:
+ *   vctl start <unit>     start a unit and the units it requires
+ *   vctl stop <unit>      stop a unit and the units that require it
+ *   vctl restart <unit>
+ *   vctl status [unit]    show the state and health of one or all units
+ *   vctl list             same as `vctl status`
+ *   vctl reload           re-read the unit files
+ *   vctl poweroff         stop every unit and power off
+ *   vctl reboot           stop every unit and reboot
+ *   vctl halt             stop every unit and halt
+ *   vctl suspend          suspend to RAM (kernel booted with `suspend`)
+ *
+ * Exits 0 on success, 1 on error, 3 from `status <unit>` if the unit is not
+ * running.
+ */
+
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include <veridian/init.h>
+#include <veridian/syscall.h>
+
+static void usage(void)
+{
+    fprintf(stderr, "usage: vctl start|stop|restart <unit>\n"
+                    "       vctl status [unit]\n"
+                    "       vctl list | reload\n"
+                    "       vctl poweroff | reboot | halt | suspend\n");
+    exit(1);
+}
+
+/* Send `op` for `unit` (may be NULL) to init; returns its status. */
+static long request(uint32_t op, const char *unit)
+{
+    char name[sizeof(INIT_REPLY_PREFIX) + 12];
+    struct init_msg m;
+    long reply_cap, cap, ret;
+
+    /* Bind the reply endpoint before init can answer */
+    reply_cap = veridian_syscall1(SYS_IPC_CREATE_ENDPOINT, 0);
+    if (reply_cap < 0)
+        return reply_cap;
+    snprintf(name, sizeof(name), INIT_REPLY_PREFIX "%d", (int)getpid());
+    ret = veridian_syscall2(SYS_IPC_BIND_ENDPOINT, reply_cap, name);
+    if (ret < 0)
+        return ret;
+
+    cap = veridian_syscall1(SYS_IPC_LOOKUP_ENDPOINT, INIT_SERVICE);
+    if (cap < 0)
+        return cap;
+    memset(&m, 0, sizeof(m));
+    m.capability = (uint64_t)cap;
+    m.opcode = op;
+    m.flags = (uint32_t)getpid();
+    if (unit)
+        memcpy(m.data, unit, strlen(unit));
+    ret = veridian_syscall4(SYS_IPC_SEND, cap, &m, sizeof(m), 0);
+    if (ret < 0)
+        return ret;
+
+    static uint8_t buf[256];
+    for (;;) {
+        long n = veridian_syscall2(SYS_IPC_RECEIVE, reply_cap, buf);
+        if (n < 0)
+            return n;
+        if (n < (long)sizeof(m))
+            continue;
+        memcpy(&m, buf, sizeof(m));
+        if (m.opcode == op)
+            return (long)m.data[0];
+    }
+}
+
+static long long now_ms(void)
+{
+    struct timespec ts;
+
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return (long long)ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+/* Status table columns (see INIT_STATUS_FILE) */
+enum { F_NAME, F_STATE, F_PID, F_RESTARTS, F_HEALTH, F_CHECK_FAILURES, F_LAST_OK,
+       F_CRITICAL, F_REASON, F_DESCRIPTION };
+
+static void show_unit(char **f)
+{
+    printf("%s - %s\n", f[F_NAME], f[F_DESCRIPTION][0] ? f[F_DESCRIPTION] : "(no description)");
+    if (f[F_REASON][0])
+        printf("  State:    %s (%s)\n", f[F_STATE], f[F_REASON]);
+    else
+        printf("  State:    %s\n", f[F_STATE]);
+    if (strcmp(f[F_PID], "0") != 0)
+        printf("  PID:      %s\n", f[F_PID]);
+    printf("  Restarts: %s\n", f[F_RESTARTS]);
+    if (strcmp(f[F_HEALTH], "-") != 0) {
+        long long age = now_ms() - atoll(f[F_LAST_OK]);
+        printf("  Health:   %s, last good check %lld.%lld s ago, %s failure(s)\n",
+               f[F_HEALTH], age / 1000, age % 1000 / 100, f[F_CHECK_FAILURES]);
+    }
+    if (strcmp(f[F_CRITICAL], "no") != 0)
+        printf("  Critical: %s on failure\n", f[F_CRITICAL]);
+}
+
+/* Print the status table, or one unit; returns the exit status. */
+static int status(const char *unit)
+{
+    char line[512];
+    int found = 0, running = 0;
+    FILE *f = fopen(INIT_STATUS_FILE, "r");
+
+    if (!f) {
+        fprintf(stderr, "vctl: cannot read %s (is init running?)\n", INIT_STATUS_FILE);
+        return 1;
+    }
+    if (!unit)
+        printf("%-16s %-9s %6s %8s %-7s %s\n", "UNIT", "STATE", "PID", "RESTARTS", "HEALTH",
+               "DESCRIPTION");
+    while (fgets(line, sizeof(line), f)) {
+        char *field[INIT_STATUS_FIELDS] = { 0 };
+        char *p = line;
+
+        line[strcspn(line, "\n")] = '\0';
+        for (int i = 0; i < INIT_STATUS_FIELDS && p; i++) {
+            field[i] = p;
+            p = strchr(p, '\t');
+            if (p)
+                *p++ = '\0';
+        }
+        if (!field[INIT_STATUS_FIELDS - 1])
+            continue;
+        if (!unit) {
+            printf("%-16s %-9s %6s %8s %-7s %s\n", field[F_NAME], field[F_STATE],
+                   strcmp(field[F_PID], "0") ? field[F_PID] : "-", field[F_RESTARTS],
+                   field[F_HEALTH], field[F_DESCRIPTION]);
+            continue;
+        }
+        if (strcmp(field[F_NAME], unit) != 0)
+            continue;
+        found = 1;
+        running = strcmp(field[F_STATE], "running") == 0;
+        show_unit(field);
+    }
+    fclose(f);
+
+    if (unit && !found) {
+        fprintf(stderr, "vctl: no unit named %s\n", unit);
+        return 1;
+    }
+    return unit && !running ? 3 : 0;
+}
+
+int main(int argc, char **argv)
+{
+    static const struct {
+        const char *cmd;
+        uint32_t op;
+        int wants_unit;
+    } ops[] = {
+        { "start",    INIT_OP_START,    1 },
+        { "stop",     INIT_OP_STOP,     1 },
+        { "restart",  INIT_OP_RESTART,  1 },
+        { "reload",   INIT_OP_RELOAD,   0 },
+        { "poweroff", INIT_OP_POWEROFF, 0 },
+        { "reboot",   INIT_OP_REBOOT,   0 },
+        { "halt",     INIT_OP_HALT,     0 },
+        { "suspend",  INIT_OP_SUSPEND,  0 },
+    };
+
+    if (argc < 2)
+        usage();
+    if (strcmp(argv[1], "status") == 0 || strcmp(argv[1], "list") == 0) {
+        if (argc > 3 || (argc == 3 && argv[1][0] == 'l'))
+            usage();
+        return status(argc == 3 ? argv[2] : NULL);
+    }
+
+    for (size_t i = 0; i < sizeof(ops) / sizeof(ops[0]); i++) {
+        if (strcmp(argv[1], ops[i].cmd) != 0)
+            continue;
+        int wants_unit = ops[i].wants_unit;
+        if (argc != 2 + wants_unit)
+            usage();
+        if (wants_unit && strlen(argv[2]) > INIT_NAME_MAX) {
+            fprintf(stderr, "vctl: unit name too long: %s\n", argv[2]);
+            return 1;
+        }
+
+        long ret = request(ops[i].op, wants_unit ? argv[2] : NULL);
+        if (ret < 0) {
+            fprintf(stderr, "vctl: %s%s%s: %s\n", argv[1], wants_unit ? " " : "",
+                    wants_unit ? argv[2] : "", strerror((int)-ret));
+            return 1;
+        }
+        return 0;
+    }
+    usage();
+    return 1;
+}
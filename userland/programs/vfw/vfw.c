@@ -0,0 +1,421 @@
+/*
+ * vfw -- VeridianOS packet filter control
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Manages the INPUT, OUTPUT and FORWARD chains of the kernel packet filter
+ * through SYS_NET_FIREWALL. Rules are evaluated in order and the first
+ * match decides; the chain policy applies when none does.
+ *
+ * Usage:
+ *   vfw list
+ *   vfw append <chain> <match...> -j <target>
+ *   vfw insert <chain> <pos> <match...> -j <target>
+ *   vfw delete <id>
+ *   vfw flush [chain]
+ *   vfw policy <chain> accept|drop
+ *   vfw conntrack
+ *   vfw stats
+ *   vfw zero
+ *
+ * Matches: -p tcp|udp|icmp, -s [!]a.b.c.d[/len], -d [!]a.b.c.d[/len],
+ * --sport n[:m], --dport n[:m], -i <dev>, -o <dev>,
+ * --state new,established,related,invalid. Targets: accept, drop, reject,
+ * log. A --state list adds one rule per state.
+ *
+ * Every command needs root.
+ */
+
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <veridian/syscall.h>
+
+/* Rule layout of FW_APPEND/FW_INSERT/FW_LIST (kernel FwRule). */
+struct fw_rule {
+    uint64_t id;
+    uint64_t packets;
+    uint64_t bytes;
+    uint8_t  chain;
+    uint8_t  action;
+    uint8_t  protocol;
+    uint8_t  state;
+    uint8_t  flags;
+    uint8_t  src_prefix;
+    uint8_t  dst_prefix;
+    uint8_t  pad;
+    uint8_t  src_addr[4];
+    uint8_t  dst_addr[4];
+    uint16_t src_port[2];
+    uint16_t dst_port[2];
+    char     in_interface[16];
+    char     out_interface[16];
+};
+
+/* Layout returned by FW_STATS (kernel FwStats). */
+struct fw_stats {
+    uint64_t packets;
+    uint64_t dropped;
+    uint64_t connections;
+    uint64_t max_connections;
+    uint8_t  policy[3];
+    uint8_t  pad[5];
+};
+
+/* Record layout returned by FW_CONNTRACK (kernel FwConn). */
+struct fw_conn {
+    uint8_t  src_addr[4];
+    uint8_t  dst_addr[4];
+    uint16_t src_port;
+    uint16_t dst_port;
+    uint8_t  protocol;
+    uint8_t  state;
+    uint8_t  pad[2];
+    uint64_t packets;
+    uint64_t bytes;
+    uint64_t expires;
+};
+
+#define FW_NEGATE_SRC 1
+#define FW_NEGATE_DST 2
+
+#define MAX_RULES 256
+#define MAX_CONNS 4096
+
+static const char *chain_names[] = { "INPUT", "OUTPUT", "FORWARD" };
+static const char *action_names[] = { "ACCEPT", "DROP", "REJECT", "LOG" };
+static const char *state_names[] = {
+    "any", "new", "established", "related", "invalid", "time_wait",
+};
+
+static long fw_ctl(long op, long a1, long a2)
+{
+    long r = veridian_syscall3(SYS_NET_FIREWALL, op, a1, a2);
+    if (r < 0) {
+        fprintf(stderr, "vfw: %s\n", strerror((int)-r));
+        exit(1);
+    }
+    return r;
+}
+
+static void usage(void)
+{
+    fprintf(stderr,
+            "usage: vfw list | conntrack | stats | zero\n"
+            "       vfw append <chain> <match...> -j <target>\n"
+            "       vfw insert <chain> <pos> <match...> -j <target>\n"
+            "       vfw delete <id>\n"
+            "       vfw flush [chain]\n"
+            "       vfw policy <chain> accept|drop\n");
+    exit(2);
+}
+
+static int lookup(const char *name, const char *const *names, int count)
+{
+    for (int i = 0; i < count; i++)
+        if (!strcasecmp(name, names[i]))
+            return i;
+    return -1;
+}
+
+static int parse_chain(const char *name)
+{
+    int chain = lookup(name, chain_names, 3);
+    if (chain < 0) {
+        fprintf(stderr, "vfw: unknown chain '%s'\n", name);
+        exit(2);
+    }
+    return chain;
+}
+
+/* Parse "[!]a.b.c.d[/len]"; sets `negate` when prefixed with '!'. */
+static void parse_cidr(const char *s, uint8_t addr[4], uint8_t *prefix, int *negate)
+{
+    const char *p = s;
+    char *end;
+
+    *negate = (*p == '!');
+    if (*negate)
+        p++;
+    for (int i = 0; i < 4; i++) {
+        unsigned long v = strtoul(p, &end, 10);
+        if (end == p || v > 255 || (i < 3 && *end != '.'))
+            goto bad;
+        addr[i] = (uint8_t)v;
+        p = end + (i < 3);
+    }
+    *prefix = 32;
+    if (*end == '/') {
+        unsigned long len = strtoul(end + 1, &end, 10);
+        if (len > 32)
+            goto bad;
+        *prefix = (uint8_t)len;
+    }
+    if (*end == '\0')
+        return;
+bad:
+    fprintf(stderr, "vfw: invalid address '%s'\n", s);
+    exit(2);
+}
+
+/* Parse "n" or "n:m" into an inclusive range. */
+static void parse_ports(const char *s, uint16_t range[2])
+{
+    char *end;
+    unsigned long lo = strtoul(s, &end, 10), hi = lo;
+
+    if (*end == ':')
+        hi = strtoul(end + 1, &end, 10);
+    if (end == s || *end != '\0' || lo == 0 || hi > 65535 || lo > hi) {
+        fprintf(stderr, "vfw: invalid port range '%s'\n", s);
+        exit(2);
+    }
+    range[0] = (uint16_t)lo;
+    range[1] = (uint16_t)hi;
+}
+
+static void copy_name(char dst[16], const char *src)
+{
+    if (strlen(src) > 15) {
+        fprintf(stderr, "vfw: interface name '%s' too long\n", src);
+        exit(2);
+    }
+    strcpy(dst, src);
+}
+
+static int add_rule(int insert, int argc, char **argv)
+{
+    struct fw_rule rule;
+    char *states = NULL;
+    long pos = 0;
+    int negate, i = 1;
+
+    if (argc < 1)
+        usage();
+    memset(&rule, 0, sizeof(rule));
+    rule.chain = (uint8_t)parse_chain(argv[0]);
+    rule.action = 0xff;
+    if (insert) {
+        if (argc < 2)
+            usage();
+        pos = atol(argv[i++]);
+    }
+
+    for (; i < argc; i++) {
+        const char *opt = argv[i];
+        if (i + 1 >= argc)
+            usage();
+        const char *val = argv[++i];
+
+        if (!strcmp(opt, "-p")) {
+            if (!strcmp(val, "tcp"))
+                rule.protocol = 6;
+            else if (!strcmp(val, "udp"))
+                rule.protocol = 17;
+            else if (!strcmp(val, "icmp"))
+                rule.protocol = 1;
+            else
+                usage();
+        } else if (!strcmp(opt, "-s")) {
+            parse_cidr(val, rule.src_addr, &rule.src_prefix, &negate);
+            if (negate)
+                rule.flags |= FW_NEGATE_SRC;
+        } else if (!strcmp(opt, "-d")) {
+            parse_cidr(val, rule.dst_addr, &rule.dst_prefix, &negate);
+            if (negate)
+                rule.flags |= FW_NEGATE_DST;
+        } else if (!strcmp(opt, "--sport")) {
+            parse_ports(val, rule.src_port);
+        } else if (!strcmp(opt, "--dport")) {
+            parse_ports(val, rule.dst_port);
+        } else if (!strcmp(opt, "-i")) {
+            copy_name(rule.in_interface, val);
+        } else if (!strcmp(opt, "-o")) {
+            copy_name(rule.out_interface, val);
+        } else if (!strcmp(opt, "--state")) {
+            states = (char *)val;
+        } else if (!strcmp(opt, "-j")) {
+            int action = lookup(val, action_names, 4);
+            if (action < 0) {
+                fprintf(stderr, "vfw: unknown target '%s'\n", val);
+                return 2;
+            }
+            rule.action = (uint8_t)action;
+        } else {
+            usage();
+        }
+    }
+    if (rule.action == 0xff)
+        usage();
+
+    if (!states) {
+        printf("%ld\n", fw_ctl(insert ? FW_INSERT : FW_APPEND, (long)&rule, pos));
+        return 0;
+    }
+    for (char *state = strtok(states, ","); state; state = strtok(NULL, ",")) {
+        int n = lookup(state, state_names, 5);
+        if (n <= 0) {
+            fprintf(stderr, "vfw: unknown state '%s'\n", state);
+            return 2;
+        }
+        rule.state = (uint8_t)n;
+        printf("%ld\n", fw_ctl(insert ? FW_INSERT : FW_APPEND, (long)&rule, pos));
+        /* Keep the states in command-line order when inserting. */
+        if (insert)
+            pos++;
+    }
+    return 0;
+}
+
+static const char *proto_name(uint8_t protocol)
+{
+    switch (protocol) {
+    case 0:  return "all";
+    case 1:  return "icmp";
+    case 6:  return "tcp";
+    case 17: return "udp";
+    default: return "?";
+    }
+}
+
+static void print_cidr(const char *opt, const uint8_t addr[4], uint8_t prefix, int negate)
+{
+    if (!prefix && !negate)
+        return;
+    printf(" %s %s%u.%u.%u.%u/%u", opt, negate ? "!" : "", addr[0], addr[1], addr[2],
+           addr[3], prefix);
+}
+
+static void print_ports(const char *opt, const uint16_t range[2])
+{
+    if (!range[0] && !range[1])
+        return;
+    if (range[0] == range[1])
+        printf(" %s %u", opt, range[0]);
+    else
+        printf(" %s %u:%u", opt, range[0], range[1]);
+}
+
+static int list(void)
+{
+    static struct fw_rule rules[MAX_RULES];
+    struct fw_stats stats;
+    long n = fw_ctl(FW_LIST, (long)rules, sizeof(rules));
+
+    fw_ctl(FW_STATS, (long)&stats, sizeof(stats));
+    for (int chain = 0; chain < 3; chain++) {
+        printf("Chain %s (policy %s)\n", chain_names[chain],
+               stats.policy[chain] ? "DROP" : "ACCEPT");
+        printf("%5s %10s %12s  %-7s %s\n", "id", "pkts", "bytes", "target", "match");
+        for (long i = 0; i < n; i++) {
+            const struct fw_rule *r = &rules[i];
+            if (r->chain != chain)
+                continue;
+            printf("%5llu %10llu %12llu  %-7s %s", (unsigned long long)r->id,
+                   (unsigned long long)r->packets, (unsigned long long)r->bytes,
+                   r->action < 4 ? action_names[r->action] : "?", proto_name(r->protocol));
+            print_cidr("-s", r->src_addr, r->src_prefix, r->flags & FW_NEGATE_SRC);
+            print_cidr("-d", r->dst_addr, r->dst_prefix, r->flags & FW_NEGATE_DST);
+            print_ports("--sport", r->src_port);
+            print_ports("--dport", r->dst_port);
+            if (r->in_interface[0])
+                printf(" -i %.15s", r->in_interface);
+            if (r->out_interface[0])
+                printf(" -o %.15s", r->out_interface);
+            if (r->state)
+                printf(" --state %s", state_names[r->state < 6 ? r->state : 0]);
+            printf("\n");
+        }
+        if (chain < 2)
+            printf("\n");
+    }
+    return 0;
+}
+
+static int conntrack(void)
+{
+    struct fw_conn *conns = malloc(MAX_CONNS * sizeof(*conns));
+    long n;
+
+    if (!conns) {
+        fprintf(stderr, "vfw: out of memory\n");
+        return 1;
+    }
+    n = fw_ctl(FW_CONNTRACK, (long)conns, MAX_CONNS * sizeof(*conns));
+    for (long i = 0; i < n; i++) {
+        const struct fw_conn *c = &conns[i];
+        printf("%-4s %-11s src=%u.%u.%u.%u dst=%u.%u.%u.%u", proto_name(c->protocol),
+               c->state < 6 ? state_names[c->state] : "?", c->src_addr[0], c->src_addr[1],
+               c->src_addr[2], c->src_addr[3], c->dst_addr[0], c->dst_addr[1],
+               c->dst_addr[2], c->dst_addr[3]);
+        if (c->protocol == 6 || c->protocol == 17)
+            printf(" sport=%u dport=%u", c->src_port, c->dst_port);
+        printf(" packets=%llu bytes=%llu expires=%llu\n", (unsigned long long)c->packets,
+               (unsigned long long)c->bytes, (unsigned long long)c->expires);
+    }
+    free(conns);
+    return 0;
+}
+
+static int stats(void)
+{
+    struct fw_stats s;
+
+    fw_ctl(FW_STATS, (long)&s, sizeof(s));
+    printf("packets:      %llu\n", (unsigned long long)s.packets);
+    printf("dropped:      %llu\n", (unsigned long long)s.dropped);
+    printf("connections:  %llu/%llu\n", (unsigned long long)s.connections,
+           (unsigned long long)s.max_connections);
+    for (int chain = 0; chain < 3; chain++)
+        printf("policy %-7s %s\n", chain_names[chain], s.policy[chain] ? "DROP" : "ACCEPT");
+    return 0;
+}
+
+int main(int argc, char **argv)
+{
+    const char *cmd;
+
+    if (argc < 2)
+        usage();
+    cmd = argv[1];
+    argc -= 2;
+    argv += 2;
+
+    if (!strcmp(cmd, "list") || !strcmp(cmd, "ls"))
+        return list();
+    if (!strcmp(cmd, "append") || !strcmp(cmd, "-A"))
+        return add_rule(0, argc, argv);
+    if (!strcmp(cmd, "insert") || !strcmp(cmd, "-I"))
+        return add_rule(1, argc, argv);
+    if ((!strcmp(cmd, "delete") || !strcmp(cmd, "-D")) && argc == 1) {
+        fw_ctl(FW_DELETE, atol(argv[0]), 0);
+        return 0;
+    }
+    if (!strcmp(cmd, "flush") || !strcmp(cmd, "-F")) {
+        fw_ctl(FW_FLUSH, argc > 0 ? parse_chain(argv[0]) : FW_CHAIN_ALL, 0);
+        return 0;
+    }
+    if ((!strcmp(cmd, "policy") || !strcmp(cmd, "-P")) && argc == 2) {
+        int chain = parse_chain(argv[0]);
+        if (!strcasecmp(argv[1], "accept"))
+            fw_ctl(FW_POLICY, chain, FW_POLICY_ACCEPT);
+        else if (!strcasecmp(argv[1], "drop"))
+            fw_ctl(FW_POLICY, chain, FW_POLICY_DROP);
+        else
+            usage();
+        return 0;
+    }
+    if (!strcmp(cmd, "conntrack"))
+        return conntrack();
+    if (!strcmp(cmd, "stats"))
+        return stats();
+    if (!strcmp(cmd, "zero") || !strcmp(cmd, "-Z")) {
+        fw_ctl(FW_ZERO, 0, 0);
+        return 0;
+    }
+    usage();
+    return 2;
+}
@@ -0,0 +1,131 @@
+/*
+ * vscreenshot -- VeridianOS screenshot and screen recording tool
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Front end for the compositor capture API (SYS_SCREENSHOT). Captures are
+ * taken from the last composited frame, so the mouse pointer is not shown.
+ *
+ * Usage:
+ *   vscreenshot [-w window] [-d seconds] [file]
+ *   vscreenshot record [-w window] [-i interval] [-n frames] dir
+ *   vscreenshot stop | status
+ *
+ * The file is written as PNG unless its name ends in `.bmp`; the default
+ * is screenshot.png in the current directory. `window` is a window ID or
+ * `focused`; without -w the whole screen is captured. A recording writes
+ * every `interval`-th frame (default 6, about 10 fps) to dir as
+ * frame-00000.png, frame-00001.png, ... until stopped or `frames` have
+ * been written. All commands need root.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/syscall.h>
+
+static void usage(void)
+{
+    fprintf(stderr,
+            "usage: vscreenshot [-w window|focused] [-d seconds] [file]\n"
+            "       vscreenshot record [-w window|focused] [-i interval] [-n frames] dir\n"
+            "       vscreenshot stop | status\n");
+    exit(2);
+}
+
+static long screenshot(long op, long a1, long a2, long a3, long a4)
+{
+    long r = veridian_syscall5(SYS_SCREENSHOT, op, a1, a2, a3, a4);
+    if (r < 0) {
+        const char *why = "";
+        if (getuid() != 0)
+            why = ": must be root";
+        else if (r == -8)
+            why = op >= SCREENSHOT_RECORD_STOP ? ": not recording"
+                                                : ": desktop not running";
+        else if (r == -4)
+            why = ": no such window or directory";
+        else if (r == -20)
+            why = ": already recording";
+        fprintf(stderr, "vscreenshot: failed (%ld)%s\n", r, why);
+        exit(1);
+    }
+    return r;
+}
+
+static long parse_window(const char *arg)
+{
+    char *end;
+    long wid;
+
+    if (strcmp(arg, "focused") == 0)
+        return SCREENSHOT_FOCUSED;
+    wid = strtol(arg, &end, 10);
+    if (*end != '\0' || wid <= 0)
+        usage();
+    return wid;
+}
+
+static long parse_count(const char *arg)
+{
+    char *end;
+    long n = strtol(arg, &end, 10);
+    if (*end != '\0' || n < 0)
+        usage();
+    return n;
+}
+
+int main(int argc, char **argv)
+{
+    const char *cmd = argc > 1 ? argv[1] : "";
+    long window = 0, interval = 0, frames = 0, delay = 0;
+    long r;
+    int record = strcmp(cmd, "record") == 0;
+    int i;
+
+    if (strcmp(cmd, "stop") == 0 && argc == 2) {
+        r = screenshot(SCREENSHOT_RECORD_STOP, 0, 0, 0, 0);
+        printf("recording stopped: %ld frames\n", r);
+        return 0;
+    }
+    if (strcmp(cmd, "status") == 0 && argc == 2) {
+        r = veridian_syscall5(SYS_SCREENSHOT, SCREENSHOT_RECORD_STATUS, 0, 0, 0, 0);
+        if (r < 0)
+            printf("not recording\n");
+        else
+            printf("recording: %ld frames\n", r);
+        return 0;
+    }
+
+    for (i = record ? 2 : 1; i < argc && argv[i][0] == '-'; i++) {
+        if (strcmp(argv[i], "-w") == 0 && i + 1 < argc)
+            window = parse_window(argv[++i]);
+        else if (strcmp(argv[i], "-d") == 0 && i + 1 < argc && !record)
+            delay = parse_count(argv[++i]);
+        else if (strcmp(argv[i], "-i") == 0 && i + 1 < argc && record)
+            interval = parse_count(argv[++i]);
+        else if (strcmp(argv[i], "-n") == 0 && i + 1 < argc && record)
+            frames = parse_count(argv[++i]);
+        else
+            usage();
+    }
+
+    if (record) {
+        if (i != argc - 1)
+            usage();
+        screenshot(SCREENSHOT_RECORD_START, (long)argv[i], window, interval, frames);
+        printf("recording to %s (stop with `vscreenshot stop`)\n", argv[i]);
+        return 0;
+    }
+
+    if (i < argc - 1)
+        usage();
+    const char *path = i < argc ? argv[i] : "screenshot.png";
+    if (delay > 0)
+        sleep((unsigned int)delay);
+    r = screenshot(SCREENSHOT_CAPTURE, (long)path, window, 0, 0);
+    printf("%s: %ld bytes\n", path, r);
+    return 0;
+}
@@ -0,0 +1,280 @@
+/*
+ * VeridianOS Remote Shell Daemon -- vsshd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * User authentication (RFC 4252). Only the "publickey" method with
+ * ssh-ed25519 keys is offered; there is no password login.
+ *
+ * A key is accepted for a user when its blob appears in the user's
+ * ~/.ssh/authorized_keys as an "ssh-ed25519 <base64> [comment]" line.
+ * Options in front of the key type are skipped, not enforced. Like
+ * OpenSSH's StrictModes, the file is ignored if anyone other than the
+ * user or root owns it or can write to it.
+ */
+
+#include <pwd.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <veridian/syscall.h>
+
+#include "vsshd.h"
+
+#define LINE_LEN    4096
+
+/* ========================================================================= */
+/* authorized_keys                                                           */
+/* ========================================================================= */
+
+/* Whether `line` holds an ssh-ed25519 key whose blob equals `blob`. */
+static int line_matches(const char *line, const uint8_t *blob, uint32_t blob_len)
+{
+    static const char type[] = SSH_HOSTKEY_ALG " ";
+    uint8_t decoded[128];
+    const char *p = line, *end;
+    long n;
+
+    if (*p == '#')
+        return 0;
+    for (;;) {
+        p = strstr(p, type);
+        if (!p)
+            return 0;
+        if (p == line || p[-1] == ' ' || p[-1] == '\t')
+            break;
+        p++;
+    }
+    p += sizeof(type) - 1;
+    while (*p == ' ' || *p == '\t')
+        p++;
+    end = p;
+    while (*end && *end != ' ' && *end != '\t' && *end != '\n' && *end != '\r')
+        end++;
+
+    n = base64_decode(p, (size_t)(end - p), decoded, sizeof(decoded));
+    return n == (long)blob_len && memcmp(decoded, blob, blob_len) == 0;
+}
+
+static int key_authorized(const struct passwd *pw, const uint8_t *blob,
+                          uint32_t blob_len)
+{
+    char path[512], line[LINE_LEN];
+    struct stat st;
+    FILE *f;
+    int found = 0;
+
+    snprintf(path, sizeof(path), "%s/%s", pw->pw_dir ? pw->pw_dir : "",
+             VSSHD_AUTH_KEYS);
+    if (stat(path, &st) < 0)
+        return 0;
+    if ((st.st_uid != pw->pw_uid && st.st_uid != 0) ||
+        (st.st_mode & (S_IWGRP | S_IWOTH))) {
+        fprintf(stderr, "vsshd: ignoring %s: bad ownership or mode\n", path);
+        return 0;
+    }
+
+    f = fopen(path, "r");
+    if (!f)
+        return 0;
+    while (!found && fgets(line, sizeof(line), f))
+        found = line_matches(line, blob, blob_len);
+    fclose(f);
+    return found;
+}
+
+/* ========================================================================= */
+/* USERAUTH_REQUEST handling                                                 */
+/* ========================================================================= */
+
+static int send_failure(ssh_conn_t *c)
+{
+    packet_begin(c, SSH_MSG_USERAUTH_FAILURE);
+    buf_put_cstring(&c->out, "publickey");
+    buf_put_u8(&c->out, 0);     /* partial success */
+    return packet_send(c);
+}
+
+/* Check an Ed25519 signature over the RFC 4252 section 7 message. */
+static int signature_valid(ssh_conn_t *c, const char *user, const char *service,
+                           const uint8_t *blob, uint32_t blob_len,
+                           const uint8_t *key, const uint8_t *sig_blob,
+                           uint32_t sig_blob_len)
+{
+    static sshbuf_t signed_data, sig_buf;
+    const uint8_t *alg, *sig;
+    uint32_t alg_len, sig_len;
+
+    buf_reset(&sig_buf);
+    buf_put_bytes(&sig_buf, sig_blob, sig_blob_len);
+    alg = buf_get_string(&sig_buf, &alg_len);
+    sig = buf_get_string(&sig_buf, &sig_len);
+    if (!alg || !sig || alg_len != strlen(SSH_HOSTKEY_ALG) ||
+        memcmp(alg, SSH_HOSTKEY_ALG, alg_len) != 0 || sig_len != 64)
+        return 0;
+
+    buf_reset(&signed_data);
+    buf_put_string(&signed_data, c->session_id, 32);
+    buf_put_u8(&signed_data, SSH_MSG_USERAUTH_REQUEST);
+    buf_put_cstring(&signed_data, user);
+    buf_put_cstring(&signed_data, service);
+    buf_put_cstring(&signed_data, "publickey");
+    buf_put_u8(&signed_data, 1);
+    buf_put_cstring(&signed_data, SSH_HOSTKEY_ALG);
+    buf_put_string(&signed_data, blob, blob_len);
+    if (signed_data.error)
+        return 0;
+
+    {
+        crypto_verify_args_t args = {
+            (size_t)key, 32,
+            (size_t)signed_data.data, signed_data.len,
+            (size_t)sig, 64,
+        };
+        return veridian_syscall2(SYS_CRYPTO_VERIFY, CRYPTO_SIG_ED25519,
+                                 &args) == 1;
+    }
+}
+
+/*
+ * Handle one "publickey" request (c->in positioned after the method name).
+ * Returns 1 if the user is authenticated, 2 if a key query was answered
+ * with PK_OK, 0 if the key is not accepted and -1 on error.
+ */
+static int try_publickey(ssh_conn_t *c, const char *user, const char *service,
+                         const struct passwd *pw)
+{
+    static sshbuf_t key_buf;
+    const uint8_t *blob, *sig_blob = NULL, *key;
+    uint32_t blob_len, sig_blob_len = 0, key_len;
+    char alg[64], key_type[64];
+    int has_sig;
+
+    has_sig = buf_get_u8(&c->in);
+    buf_get_cstring(&c->in, alg, sizeof(alg));
+    blob = buf_get_string(&c->in, &blob_len);
+    if (has_sig)
+        sig_blob = buf_get_string(&c->in, &sig_blob_len);
+    if (c->in.error)
+        return -1;
+
+    if (strcmp(alg, SSH_HOSTKEY_ALG) != 0 || !pw)
+        return 0;
+
+    buf_reset(&key_buf);
+    buf_put_bytes(&key_buf, blob, blob_len);
+    buf_get_cstring(&key_buf, key_type, sizeof(key_type));
+    key = buf_get_string(&key_buf, &key_len);
+    if (key_buf.error || strcmp(key_type, SSH_HOSTKEY_ALG) != 0 || key_len != 32)
+        return 0;
+
+    if (!key_authorized(pw, blob, blob_len))
+        return 0;
+
+    if (!has_sig) {
+        /* The client is asking whether this key would do */
+        packet_begin(c, SSH_MSG_USERAUTH_PK_OK);
+        buf_put_cstring(&c->out, alg);
+        buf_put_string(&c->out, blob, blob_len);
+        return packet_send(c) < 0 ? -1 : 2;
+    }
+
+    return signature_valid(c, user, service, blob, blob_len, key,
+                           sig_blob, sig_blob_len);
+}
+
+static int fill_user(ssh_user_t *user, const struct passwd *pw)
+{
+    const char *shell = (pw->pw_shell && pw->pw_shell[0]) ? pw->pw_shell : "/bin/sh";
+    const char *home = (pw->pw_dir && pw->pw_dir[0]) ? pw->pw_dir : "/";
+
+    if (strlen(pw->pw_name) >= sizeof(user->name) ||
+        strlen(home) >= sizeof(user->home) ||
+        strlen(shell) >= sizeof(user->shell))
+        return -1;
+    strcpy(user->name, pw->pw_name);
+    strcpy(user->home, home);
+    strcpy(user->shell, shell);
+    user->uid = pw->pw_uid;
+    user->gid = pw->pw_gid;
+    return 0;
+}
+
+/*
+ * Run the "ssh-userauth" service until a user is authenticated.
+ * Returns 0 with `user` filled in, or -1.
+ */
+int auth_user(ssh_conn_t *c, ssh_user_t *user)
+{
+    char service[64], name[64], method[64];
+    int type, attempts = 0;
+
+    do
+        type = packet_next(c);
+    while (type == 0);
+    if (type != SSH_MSG_SERVICE_REQUEST ||
+        buf_get_cstring(&c->in, service, sizeof(service)) < 0 ||
+        strcmp(service, "ssh-userauth") != 0) {
+        packet_disconnect(c, SSH_DISCONNECT_SERVICE_NOT_AVAILABLE,
+                          "expected ssh-userauth");
+        return -1;
+    }
+    packet_begin(c, SSH_MSG_SERVICE_ACCEPT);
+    buf_put_cstring(&c->out, service);
+    if (packet_send(c) < 0)
+        return -1;
+
+    for (;;) {
+        struct passwd *pw;
+        int ok = 0;
+
+        type = packet_next(c);
+        if (type < 0)
+            return -1;
+        if (type == 0)
+            continue;
+        if (type != SSH_MSG_USERAUTH_REQUEST) {
+            packet_unimplemented(c);
+            continue;
+        }
+
+        buf_get_cstring(&c->in, name, sizeof(name));
+        buf_get_cstring(&c->in, service, sizeof(service));
+        buf_get_cstring(&c->in, method, sizeof(method));
+        if (c->in.error) {
+            packet_disconnect(c, SSH_DISCONNECT_PROTOCOL_ERROR,
+                              "malformed userauth request");
+            return -1;
+        }
+        if (strcmp(service, "ssh-connection") != 0) {
+            packet_disconnect(c, SSH_DISCONNECT_SERVICE_NOT_AVAILABLE,
+                              "unknown service");
+            return -1;
+        }
+
+        pw = getpwnam(name);
+        if (strcmp(method, "publickey") == 0) {
+            ok = try_publickey(c, name, service, pw);
+            if (ok < 0)
+                return -1;
+            /* A key query answered with PK_OK is not an attempt */
+            if (ok == 2)
+                continue;
+        }
+
+        if (ok == 1 && fill_user(user, pw) == 0) {
+            packet_begin(c, SSH_MSG_USERAUTH_SUCCESS);
+            return packet_send(c);
+        }
+
+        /* "none" is how clients ask for the method list; don't count it */
+        if (strcmp(method, "none") != 0 && ++attempts >= VSSHD_MAX_AUTH) {
+            packet_disconnect(c, SSH_DISCONNECT_NO_MORE_AUTH_METHODS,
+                              "too many authentication failures");
+            return -1;
+        }
+        if (send_failure(c) < 0)
+            return -1;
+    }
+}
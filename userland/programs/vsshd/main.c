@@ -0,0 +1,344 @@
+/*
+ * VeridianOS Remote Shell Daemon -- vsshd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * A small SSH-2 server: enough of the protocol for a stock OpenSSH client
+ * to log in with an Ed25519 key and get a shell on a pseudo-terminal.
+ *
+ *   key exchange    curve25519-sha256 (RFC 8731), with OpenSSH strict KEX
+ *   host key        ssh-ed25519
+ *   cipher          aes128-gcm@openssh.com (RFC 5647)
+ *   user auth       publickey, ssh-ed25519 keys in ~/.ssh/authorized_keys
+ *   channels        one "session" per connection: pty-req, env, shell,
+ *                   exec, window-change
+ *
+ * All cryptography is done by the kernel (SYS_CRYPTO_*). The host key is
+ * the 32-byte Ed25519 seed in /etc/vsshd/ssh_host_ed25519_key, generated
+ * on first start together with an OpenSSH-format .pub file next to it.
+ *
+ * Each connection is served by a forked child running as root until the
+ * user is authenticated; the session process then drops to the user's
+ * credentials before executing their shell.
+ *
+ * Usage: vsshd [-p port] [-d]
+ *
+ *   -p port   listen on `port` instead of 22
+ *   -d        serve one connection in the foreground, for debugging
+ */
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <veridian/syscall.h>
+
+#include "vsshd.h"
+
+/* ========================================================================= */
+/* Utility functions                                                         */
+/* ========================================================================= */
+
+int crypto_sha256(const void *data, size_t len, uint8_t out[32])
+{
+    long r = veridian_syscall5(SYS_CRYPTO_HASH, CRYPTO_HASH_SHA256,
+                               data, len, out, 32);
+    return r == 32 ? 0 : -1;
+}
+
+static const char b64_alphabet[] =
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/* Encode with '=' padding. Returns the length written (without the NUL),
+ * or 0 if `cap` is too small. */
+size_t base64_encode(const uint8_t *in, size_t len, char *out, size_t cap)
+{
+    size_t need = (len + 2) / 3 * 4;
+    size_t i, o = 0;
+
+    if (need + 1 > cap)
+        return 0;
+
+    for (i = 0; i < len; i += 3) {
+        uint32_t v = (uint32_t)in[i] << 16;
+        if (i + 1 < len)
+            v |= (uint32_t)in[i + 1] << 8;
+        if (i + 2 < len)
+            v |= in[i + 2];
+        out[o++] = b64_alphabet[(v >> 18) & 63];
+        out[o++] = b64_alphabet[(v >> 12) & 63];
+        out[o++] = i + 1 < len ? b64_alphabet[(v >> 6) & 63] : '=';
+        out[o++] = i + 2 < len ? b64_alphabet[v & 63] : '=';
+    }
+    out[o] = '\0';
+    return o;
+}
+
+static int b64_value(char ch)
+{
+    const char *p = strchr(b64_alphabet, ch);
+    return (ch && p) ? (int)(p - b64_alphabet) : -1;
+}
+
+/* Decode, stopping at the first '='. Returns the decoded length, or -1 on
+ * a bad character or if `cap` is too small. */
+long base64_decode(const char *in, size_t len, uint8_t *out, size_t cap)
+{
+    uint32_t acc = 0;
+    int bits = 0;
+    size_t i, o = 0;
+
+    for (i = 0; i < len && in[i] != '='; i++) {
+        int v = b64_value(in[i]);
+        if (v < 0)
+            return -1;
+        acc = (acc << 6) | (uint32_t)v;
+        bits += 6;
+        if (bits >= 8) {
+            bits -= 8;
+            if (o == cap)
+                return -1;
+            out[o++] = (uint8_t)(acc >> bits);
+        }
+    }
+    return (long)o;
+}
+
+/* ========================================================================= */
+/* Host key                                                                  */
+/* ========================================================================= */
+
+/* Public half of an Ed25519 seed, via a throwaway signature. */
+static int host_public_key(const uint8_t seed[32], uint8_t pub[32])
+{
+    uint8_t sig[64];
+    crypto_sign_args_t args = {
+        (size_t)seed, 32, (size_t)"", 0, (size_t)sig, sizeof(sig),
+        (size_t)pub, 32,
+    };
+    long r = veridian_syscall2(SYS_CRYPTO_SIGN, CRYPTO_SIG_ED25519, &args);
+    return r == 64 ? 0 : -1;
+}
+
+/* "SHA256:<unpadded base64>" fingerprint of the host key blob */
+static void host_fingerprint(const uint8_t pub[32], char *out, size_t cap)
+{
+    static sshbuf_t blob;
+    uint8_t digest[32];
+    char b64[48];
+    size_t n;
+
+    buf_reset(&blob);
+    buf_put_cstring(&blob, SSH_HOSTKEY_ALG);
+    buf_put_string(&blob, pub, 32);
+    crypto_sha256(blob.data, blob.len, digest);
+
+    n = base64_encode(digest, sizeof(digest), b64, sizeof(b64));
+    while (n > 0 && b64[n - 1] == '=')
+        b64[--n] = '\0';
+    snprintf(out, cap, "SHA256:%s", b64);
+}
+
+static int write_public_key(const uint8_t pub[32])
+{
+    static sshbuf_t blob;
+    char b64[80], host[64] = "veridian";
+    FILE *f;
+
+    buf_reset(&blob);
+    buf_put_cstring(&blob, SSH_HOSTKEY_ALG);
+    buf_put_string(&blob, pub, 32);
+    base64_encode(blob.data, blob.len, b64, sizeof(b64));
+    gethostname(host, sizeof(host));
+
+    f = fopen(VSSHD_HOST_PUB, "w");
+    if (!f)
+        return -errno;
+    fprintf(f, "%s %s root@%s\n", SSH_HOSTKEY_ALG, b64, host);
+    return fclose(f) == 0 ? 0 : -EIO;
+}
+
+/* Load the host key, generating it on first start. */
+static int load_host_key(uint8_t seed[32], uint8_t pub[32])
+{
+    int fd = open(VSSHD_HOST_KEY, O_RDONLY);
+    if (fd >= 0) {
+        ssize_t n = read(fd, seed, 32);
+        close(fd);
+        if (n != 32) {
+            fprintf(stderr, "vsshd: %s: bad key file\n", VSSHD_HOST_KEY);
+            return -EINVAL;
+        }
+        return host_public_key(seed, pub) == 0 ? 0 : -EINVAL;
+    }
+    if (errno != ENOENT)
+        return -errno;
+
+    if (mkdir(VSSHD_KEY_DIR, 0755) < 0 && errno != EEXIST)
+        return -errno;
+    if (getentropy(seed, 32) < 0 || host_public_key(seed, pub) < 0)
+        return -EIO;
+
+    fd = open(VSSHD_HOST_KEY, O_WRONLY | O_CREAT | O_EXCL, 0600);
+    if (fd < 0)
+        return -errno;
+    if (write(fd, seed, 32) != 32) {
+        close(fd);
+        unlink(VSSHD_HOST_KEY);
+        return -EIO;
+    }
+    close(fd);
+
+    printf("vsshd: generated host key %s\n", VSSHD_HOST_KEY);
+    return write_public_key(pub);
+}
+
+/* ========================================================================= */
+/* Connections                                                               */
+/* ========================================================================= */
+
+static void format_addr(const struct sockaddr_in *sa, char *out, size_t cap)
+{
+    char ip[16] = "?";
+    inet_ntop(AF_INET, &sa->sin_addr, ip, sizeof(ip));
+    snprintf(out, cap, "%s %u", ip, ntohs(sa->sin_port));
+}
+
+/* Serve one connection from start to finish. Returns the exit status. */
+static int serve(int fd, const struct sockaddr_in *peer,
+                 const uint8_t seed[32], const uint8_t pub[32])
+{
+    static ssh_conn_t conn;
+    ssh_user_t user;
+    struct sockaddr_in local;
+    socklen_t len = sizeof(local);
+    int ret;
+
+    memset(&conn, 0, sizeof(conn));
+    conn.fd = fd;
+    memcpy(conn.host_seed, seed, 32);
+    memcpy(conn.host_pub, pub, 32);
+    format_addr(peer, conn.peer, sizeof(conn.peer));
+    if (getsockname(fd, (struct sockaddr *)&local, &len) == 0)
+        format_addr(&local, conn.local, sizeof(conn.local));
+
+    /* Unauthenticated connections get a bounded amount of time */
+    alarm(VSSHD_LOGIN_GRACE);
+
+    if (transport_version_exchange(&conn) < 0 ||
+        transport_kex(&conn, 0) < 0)
+        return 1;
+
+    ret = auth_user(&conn, &user);
+    if (ret < 0) {
+        printf("vsshd: authentication failed from %s\n", conn.peer);
+        return 1;
+    }
+    alarm(0);
+
+    printf("vsshd: accepted publickey for %s from %s\n", user.name, conn.peer);
+    return session_run(&conn, &user) < 0 ? 1 : 0;
+}
+
+static void reap_children(void)
+{
+    while (waitpid(-1, NULL, WNOHANG) > 0)
+        ;
+}
+
+/* ========================================================================= */
+/* Main                                                                      */
+/* ========================================================================= */
+
+int main(int argc, char **argv)
+{
+    uint8_t seed[32], pub[32];
+    char fingerprint[64];
+    struct sockaddr_in addr;
+    int port = VSSHD_PORT, foreground = 0, one = 1;
+    int i, s, ret;
+
+    for (i = 1; i < argc; i++) {
+        if (strcmp(argv[i], "-p") == 0 && i + 1 < argc) {
+            port = atoi(argv[++i]);
+        } else if (strcmp(argv[i], "-d") == 0) {
+            foreground = 1;
+        } else {
+            fputs("usage: vsshd [-p port] [-d]\n", stderr);
+            return 2;
+        }
+    }
+    if (port <= 0 || port > 65535) {
+        fputs("vsshd: invalid port\n", stderr);
+        return 2;
+    }
+
+    if (geteuid() != 0) {
+        fputs("vsshd: must be run as root\n", stderr);
+        return 1;
+    }
+
+    ret = load_host_key(seed, pub);
+    if (ret < 0) {
+        fprintf(stderr, "vsshd: cannot load host key: %s\n", strerror(-ret));
+        return 1;
+    }
+
+    s = socket(AF_INET, SOCK_STREAM, 0);
+    if (s < 0) {
+        perror("vsshd: socket");
+        return 1;
+    }
+    setsockopt(s, SOL_SOCKET, SO_REUSEADDR, &one, sizeof(one));
+
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons((uint16_t)port);
+    addr.sin_addr.s_addr = INADDR_ANY;
+    if (bind(s, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        listen(s, 8) < 0) {
+        perror("vsshd: bind");
+        return 1;
+    }
+
+    host_fingerprint(pub, fingerprint, sizeof(fingerprint));
+    printf("vsshd: listening on port %d, host key %s\n", port, fingerprint);
+
+    for (;;) {
+        struct sockaddr_in peer;
+        socklen_t len = sizeof(peer);
+        pid_t pid;
+        int fd;
+
+        reap_children();
+        fd = accept(s, (struct sockaddr *)&peer, &len);
+        if (fd < 0) {
+            if (errno != EINTR)
+                perror("vsshd: accept");
+            continue;
+        }
+
+        if (foreground) {
+            close(s);
+            return serve(fd, &peer, seed, pub);
+        }
+
+        fflush(stdout);
+        pid = fork();
+        if (pid == 0) {
+            close(s);
+            exit(serve(fd, &peer, seed, pub));
+        }
+        if (pid < 0)
+            perror("vsshd: fork");
+        close(fd);
+    }
+}
@@ -0,0 +1,543 @@
+/*
+ * VeridianOS Remote Shell Daemon -- vsshd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Connection protocol (RFC 4254): a single "session" channel running the
+ * user's shell, either on a pseudo-terminal ("pty-req" then "shell") or
+ * on plain pipes for non-interactive "exec" requests.
+ *
+ * The daemon pumps bytes between the channel and the child with poll(),
+ * honouring the client's window, and reports the exit status before
+ * closing the channel when the child exits.
+ */
+
+#include <errno.h>
+#include <grp.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/ioctl.h>
+#include <sys/wait.h>
+#include <veridian/syscall.h>
+
+#include "vsshd.h"
+
+#define IO_CHUNK        16384
+#define CMD_LEN         4096
+#define TERM_LEN        64
+#define CHILD_POLL_MS   200
+
+typedef struct {
+    int            open;
+    uint32_t       peer_id;         /* client's channel number */
+    uint32_t       remote_window;   /* bytes we may still send */
+    uint32_t       remote_maxpkt;
+    uint32_t       consumed;        /* bytes received since last adjust */
+    int            close_sent;
+
+    int            pty_master, pty_slave;
+    struct winsize ws;
+    char           term[TERM_LEN];
+
+    int            in_fd;           /* child's stdin */
+    int            out_fd;          /* child's stdout (and stderr on a pty) */
+    int            err_fd;          /* child's stderr without a pty */
+    pid_t          pid;             /* 0 before the shell starts, -1 after */
+    int            exited;          /* reaped, output not yet drained */
+    int            status;
+} channel_t;
+
+/* ========================================================================= */
+/* Child process                                                             */
+/* ========================================================================= */
+
+static char *env_entry(char *buf, size_t cap, const char *name,
+                       const char *value)
+{
+    snprintf(buf, cap, "%s=%s", name, value);
+    return buf;
+}
+
+/* Become the user and execute their shell; does not return. */
+static void exec_shell(const ssh_conn_t *c, const ssh_user_t *user,
+                       const channel_t *ch, const char *command)
+{
+    static char e_home[300], e_shell[300], e_user[80], e_logname[80];
+    static char e_path[64], e_term[80], e_conn[160];
+    char *envp[8], *argv[4], argv0[sizeof(user->shell) + 1];
+    const char *base;
+    int n = 0;
+
+    if (initgroups(user->name, user->gid) != 0 ||
+        setgid(user->gid) != 0 ||
+        setuid(user->uid) != 0) {
+        fputs("vsshd: cannot set user credentials\r\n", stderr);
+        _exit(1);
+    }
+    if (chdir(user->home) != 0)
+        chdir("/");
+
+    envp[n++] = env_entry(e_home, sizeof(e_home), "HOME", user->home);
+    envp[n++] = env_entry(e_shell, sizeof(e_shell), "SHELL", user->shell);
+    envp[n++] = env_entry(e_user, sizeof(e_user), "USER", user->name);
+    envp[n++] = env_entry(e_logname, sizeof(e_logname), "LOGNAME", user->name);
+    envp[n++] = env_entry(e_path, sizeof(e_path), "PATH",
+                          user->uid == 0 ? "/bin:/usr/bin:/sbin:/usr/sbin"
+                                         : "/bin:/usr/bin");
+    snprintf(e_conn, sizeof(e_conn), "SSH_CONNECTION=%s %s", c->peer, c->local);
+    envp[n++] = e_conn;
+    if (ch->pty_slave >= 0)
+        envp[n++] = env_entry(e_term, sizeof(e_term), "TERM", ch->term);
+    envp[n] = NULL;
+
+    base = strrchr(user->shell, '/');
+    base = base ? base + 1 : user->shell;
+    if (command) {
+        argv[0] = (char *)base;
+        argv[1] = "-c";
+        argv[2] = (char *)command;
+        argv[3] = NULL;
+    } else {
+        /* Login shells are started with a leading '-' in argv[0] */
+        snprintf(argv0, sizeof(argv0), "-%s", base);
+        argv[0] = argv0;
+        argv[1] = NULL;
+    }
+
+    execve(user->shell, argv, envp);
+    fprintf(stderr, "vsshd: cannot execute %s\r\n", user->shell);
+    _exit(127);
+}
+
+/* Start the shell (or `command`) on the channel's pty or on pipes. */
+static int spawn(ssh_conn_t *c, const ssh_user_t *user, channel_t *ch,
+                 const char *command)
+{
+    int in[2] = { -1, -1 }, out[2] = { -1, -1 }, err[2] = { -1, -1 };
+    pid_t pid;
+
+    if (ch->pty_master < 0 && (pipe(in) < 0 || pipe(out) < 0 || pipe(err) < 0))
+        goto fail;
+
+    pid = fork();
+    if (pid < 0)
+        goto fail;
+
+    if (pid == 0) {
+        setsid();
+        if (ch->pty_slave >= 0) {
+            ioctl(ch->pty_slave, TIOCSCTTY, 0);
+            dup2(ch->pty_slave, 0);
+            dup2(ch->pty_slave, 1);
+            dup2(ch->pty_slave, 2);
+            close(ch->pty_master);
+            if (ch->pty_slave > 2)
+                close(ch->pty_slave);
+        } else {
+            dup2(in[0], 0);
+            dup2(out[1], 1);
+            dup2(err[1], 2);
+            close(in[0]); close(in[1]);
+            close(out[0]); close(out[1]);
+            close(err[0]); close(err[1]);
+        }
+        close(c->fd);
+        exec_shell(c, user, ch, command);
+    }
+
+    ch->pid = pid;
+    if (ch->pty_master >= 0) {
+        close(ch->pty_slave);
+        ch->pty_slave = -1;
+        ch->in_fd = ch->out_fd = ch->pty_master;
+    } else {
+        close(in[0]);
+        close(out[1]);
+        close(err[1]);
+        ch->in_fd = in[1];
+        ch->out_fd = out[0];
+        ch->err_fd = err[0];
+    }
+    return 0;
+
+fail:
+    if (in[0] >= 0) { close(in[0]); close(in[1]); }
+    if (out[0] >= 0) { close(out[0]); close(out[1]); }
+    if (err[0] >= 0) { close(err[0]); close(err[1]); }
+    return -1;
+}
+
+/* ========================================================================= */
+/* Channel messages                                                          */
+/* ========================================================================= */
+
+static void close_fd(int *fd)
+{
+    if (*fd >= 0)
+        close(*fd);
+    *fd = -1;
+}
+
+/* Close the child's side of the channel. On a pty, in_fd and out_fd are
+ * both the master. */
+static void close_child_io(channel_t *ch)
+{
+    if (ch->in_fd == ch->pty_master)
+        ch->in_fd = -1;
+    if (ch->out_fd == ch->pty_master)
+        ch->out_fd = -1;
+    close_fd(&ch->in_fd);
+    close_fd(&ch->out_fd);
+    close_fd(&ch->err_fd);
+    close_fd(&ch->pty_master);
+    close_fd(&ch->pty_slave);
+}
+
+static int write_fd(int fd, const uint8_t *data, size_t len)
+{
+    while (len > 0) {
+        ssize_t n = write(fd, data, len);
+        if (n < 0 && errno == EINTR)
+            continue;
+        if (n <= 0)
+            return -1;
+        data += n;
+        len -= (size_t)n;
+    }
+    return 0;
+}
+
+static int send_channel_status(ssh_conn_t *c, channel_t *ch, uint8_t type)
+{
+    packet_begin(c, type);
+    buf_put_u32(&c->out, ch->peer_id);
+    return packet_send(c);
+}
+
+static int channel_open(ssh_conn_t *c, channel_t *ch)
+{
+    char type[64];
+    uint32_t sender, window, maxpkt;
+    uint32_t reason = 0;
+
+    buf_get_cstring(&c->in, type, sizeof(type));
+    sender = buf_get_u32(&c->in);
+    window = buf_get_u32(&c->in);
+    maxpkt = buf_get_u32(&c->in);
+    if (c->in.error)
+        return -1;
+
+    if (strcmp(type, "session") != 0)
+        reason = SSH_OPEN_UNKNOWN_CHANNEL_TYPE;
+    else if (ch->open)
+        reason = SSH_OPEN_ADMINISTRATIVELY_PROHIBITED;
+
+    if (reason) {
+        packet_begin(c, SSH_MSG_CHANNEL_OPEN_FAILURE);
+        buf_put_u32(&c->out, sender);
+        buf_put_u32(&c->out, reason);
+        buf_put_cstring(&c->out, reason == SSH_OPEN_UNKNOWN_CHANNEL_TYPE
+                                     ? "unsupported channel type"
+                                     : "one session per connection");
+        buf_put_cstring(&c->out, "");
+        return packet_send(c);
+    }
+
+    ch->open = 1;
+    ch->peer_id = sender;
+    ch->remote_window = window;
+    ch->remote_maxpkt = maxpkt;
+
+    packet_begin(c, SSH_MSG_CHANNEL_OPEN_CONFIRMATION);
+    buf_put_u32(&c->out, sender);
+    buf_put_u32(&c->out, 0);            /* our channel number */
+    buf_put_u32(&c->out, SSH_CHANNEL_WINDOW);
+    buf_put_u32(&c->out, SSH_CHANNEL_MAXPKT);
+    return packet_send(c);
+}
+
+static void read_winsize(ssh_conn_t *c, struct winsize *ws)
+{
+    ws->ws_col = (unsigned short)buf_get_u32(&c->in);
+    ws->ws_row = (unsigned short)buf_get_u32(&c->in);
+    ws->ws_xpixel = (unsigned short)buf_get_u32(&c->in);
+    ws->ws_ypixel = (unsigned short)buf_get_u32(&c->in);
+}
+
+static int channel_request(ssh_conn_t *c, const ssh_user_t *user, channel_t *ch)
+{
+    static char command[CMD_LEN];
+    char type[64];
+    int want_reply, ok = 0;
+
+    buf_get_u32(&c->in);                /* recipient: always channel 0 */
+    buf_get_cstring(&c->in, type, sizeof(type));
+    want_reply = buf_get_u8(&c->in);
+    if (c->in.error || !ch->open)
+        return -1;
+
+    if (strcmp(type, "pty-req") == 0) {
+        uint32_t modes_len;
+        int fds[2];
+
+        buf_get_cstring(&c->in, ch->term, sizeof(ch->term));
+        read_winsize(c, &ch->ws);
+        buf_get_string(&c->in, &modes_len);     /* terminal modes: ignored */
+        if (!c->in.error && ch->pty_master < 0 && ch->pid == 0 &&
+            veridian_syscall2(SYS_OPENPTY, &fds[0], &fds[1]) == 0) {
+            ch->pty_master = fds[0];
+            ch->pty_slave = fds[1];
+            ioctl(ch->pty_master, TIOCSWINSZ, &ch->ws);
+            ok = 1;
+        }
+    } else if (strcmp(type, "shell") == 0) {
+        ok = ch->pid == 0 && spawn(c, user, ch, NULL) == 0;
+    } else if (strcmp(type, "exec") == 0) {
+        ok = buf_get_cstring(&c->in, command, sizeof(command)) == 0 &&
+             ch->pid == 0 && spawn(c, user, ch, command) == 0;
+    } else if (strcmp(type, "window-change") == 0) {
+        read_winsize(c, &ch->ws);
+        if (!c->in.error && ch->pty_master >= 0)
+            ok = ioctl(ch->pty_master, TIOCSWINSZ, &ch->ws) == 0;
+    }
+    /* "env", "signal" and anything else are refused */
+
+    if (!want_reply)
+        return 0;
+    return send_channel_status(c, ch, ok ? SSH_MSG_CHANNEL_SUCCESS
+                                         : SSH_MSG_CHANNEL_FAILURE);
+}
+
+static int channel_data(ssh_conn_t *c, channel_t *ch)
+{
+    const uint8_t *data;
+    uint32_t len;
+
+    buf_get_u32(&c->in);
+    data = buf_get_string(&c->in, &len);
+    if (!data || !ch->open)
+        return -1;
+
+    /* Input after the child's stdin is gone is dropped */
+    if (ch->in_fd >= 0 && write_fd(ch->in_fd, data, len) < 0) {
+        if (ch->in_fd != ch->pty_master)
+            close(ch->in_fd);
+        ch->in_fd = -1;
+    }
+
+    ch->consumed += len;
+    if (ch->consumed >= SSH_CHANNEL_WINDOW / 2) {
+        packet_begin(c, SSH_MSG_CHANNEL_WINDOW_ADJUST);
+        buf_put_u32(&c->out, ch->peer_id);
+        buf_put_u32(&c->out, ch->consumed);
+        ch->consumed = 0;
+        return packet_send(c);
+    }
+    return 0;
+}
+
+/* Dispatch one packet. Returns 1 once the client has closed the channel. */
+static int handle_packet(ssh_conn_t *c, const ssh_user_t *user, channel_t *ch,
+                         int type)
+{
+    uint32_t n;
+
+    switch (type) {
+    case 0:             /* handled by the transport */
+        return 0;
+    case SSH_MSG_CHANNEL_OPEN:
+        return channel_open(c, ch);
+    case SSH_MSG_CHANNEL_REQUEST:
+        return channel_request(c, user, ch);
+    case SSH_MSG_CHANNEL_DATA:
+        return channel_data(c, ch);
+    case SSH_MSG_CHANNEL_EXTENDED_DATA:
+        return 0;
+    case SSH_MSG_CHANNEL_WINDOW_ADJUST:
+        buf_get_u32(&c->in);
+        n = buf_get_u32(&c->in);
+        if (c->in.error)
+            return -1;
+        ch->remote_window = n > UINT32_MAX - ch->remote_window
+                                ? UINT32_MAX : ch->remote_window + n;
+        return 0;
+    case SSH_MSG_CHANNEL_EOF:
+        /* A pty has no separate end of input */
+        if (ch->in_fd >= 0 && ch->in_fd != ch->pty_master) {
+            close(ch->in_fd);
+            ch->in_fd = -1;
+        }
+        return 0;
+    case SSH_MSG_CHANNEL_CLOSE:
+        if (!ch->close_sent)
+            send_channel_status(c, ch, SSH_MSG_CHANNEL_CLOSE);
+        ch->close_sent = 1;
+        return 1;
+    case SSH_MSG_GLOBAL_REQUEST: {
+        char name[64];
+        buf_get_cstring(&c->in, name, sizeof(name));
+        if (buf_get_u8(&c->in)) {
+            packet_begin(c, SSH_MSG_REQUEST_FAILURE);
+            return packet_send(c);
+        }
+        return 0;
+    }
+    default:
+        packet_unimplemented(c);
+        return 0;
+    }
+}
+
+/* ========================================================================= */
+/* Main loop                                                                 */
+/* ========================================================================= */
+
+/* Forward what is available on `fd` to the channel. Returns -1 at EOF. */
+static int pump(ssh_conn_t *c, channel_t *ch, int fd, int extended)
+{
+    static uint8_t buf[IO_CHUNK];
+    size_t max = IO_CHUNK;
+    ssize_t n;
+
+    if (max > ch->remote_window)
+        max = ch->remote_window;
+    if (max > ch->remote_maxpkt)
+        max = ch->remote_maxpkt;
+    if (max == 0)
+        return 0;
+
+    n = read(fd, buf, max);
+    if (n < 0 && errno == EINTR)
+        return 0;
+    if (n <= 0)
+        return -1;
+
+    packet_begin(c, extended ? SSH_MSG_CHANNEL_EXTENDED_DATA
+                             : SSH_MSG_CHANNEL_DATA);
+    buf_put_u32(&c->out, ch->peer_id);
+    if (extended)
+        buf_put_u32(&c->out, SSH_EXTENDED_DATA_STDERR);
+    buf_put_string(&c->out, buf, (size_t)n);
+    ch->remote_window -= (uint32_t)n;
+    return packet_send(c) < 0 ? -1 : 0;
+}
+
+/* Report how the child ended, then send EOF and CLOSE. */
+static int finish(ssh_conn_t *c, channel_t *ch)
+{
+    int status = ch->status;
+    uint32_t code = WIFEXITED(status) ? WEXITSTATUS(status)
+                                      : 128 + (uint32_t)WTERMSIG(status);
+
+    packet_begin(c, SSH_MSG_CHANNEL_REQUEST);
+    buf_put_u32(&c->out, ch->peer_id);
+    buf_put_cstring(&c->out, "exit-status");
+    buf_put_u8(&c->out, 0);
+    buf_put_u32(&c->out, code);
+    if (packet_send(c) < 0 ||
+        send_channel_status(c, ch, SSH_MSG_CHANNEL_EOF) < 0 ||
+        send_channel_status(c, ch, SSH_MSG_CHANNEL_CLOSE) < 0)
+        return -1;
+    ch->close_sent = 1;
+    return 0;
+}
+
+/*
+ * Serve the connection protocol until the session channel is closed or
+ * the connection drops.
+ */
+int session_run(ssh_conn_t *c, const ssh_user_t *user)
+{
+    static channel_t ch;
+    int ret = 0;
+
+    memset(&ch, 0, sizeof(ch));
+    ch.pty_master = ch.pty_slave = -1;
+    ch.in_fd = ch.out_fd = ch.err_fd = -1;
+
+    /* A child closing its stdin must not kill the daemon */
+    signal(SIGPIPE, SIG_IGN);
+
+    for (;;) {
+        struct pollfd fds[3];
+        int nfds = 1, out_slot = -1, err_slot = -1, out_ready, err_ready;
+        int status, r;
+
+        fds[0].fd = c->fd;
+        fds[0].events = POLLIN;
+        if (ch.remote_window > 0 && ch.out_fd >= 0) {
+            out_slot = nfds;
+            fds[nfds].fd = ch.out_fd;
+            fds[nfds++].events = POLLIN;
+        }
+        if (ch.remote_window > 0 && ch.err_fd >= 0) {
+            err_slot = nfds;
+            fds[nfds].fd = ch.err_fd;
+            fds[nfds++].events = POLLIN;
+        }
+        for (r = 0; r < nfds; r++)
+            fds[r].revents = 0;
+
+        r = poll(fds, (nfds_t)nfds,
+                 (ch.pid > 0 || ch.exited) ? CHILD_POLL_MS : -1);
+        if (r < 0 && errno != EINTR) {
+            ret = -1;
+            break;
+        }
+
+        if (fds[0].revents & (POLLIN | POLLHUP | POLLERR)) {
+            int type = packet_next(c);
+            if (type < 0) {
+                ret = -1;
+                break;
+            }
+            r = handle_packet(c, user, &ch, type);
+            if (r < 0) {
+                packet_disconnect(c, SSH_DISCONNECT_PROTOCOL_ERROR,
+                                  "malformed channel message");
+                ret = -1;
+                break;
+            }
+            if (r == 1)
+                break;
+        }
+
+        /* A pty master reads EOF once nothing has the slave open */
+        out_ready = out_slot > 0 && (fds[out_slot].revents & (POLLIN | POLLHUP));
+        err_ready = err_slot > 0 && (fds[err_slot].revents & (POLLIN | POLLHUP));
+        if (out_ready && pump(c, &ch, ch.out_fd, 0) < 0) {
+            if (ch.out_fd == ch.pty_master)
+                close_child_io(&ch);
+            else
+                close_fd(&ch.out_fd);
+        }
+        if (err_ready && pump(c, &ch, ch.err_fd, 1) < 0)
+            close_fd(&ch.err_fd);
+
+        if (ch.pid > 0 && waitpid(ch.pid, &status, WNOHANG) == ch.pid) {
+            ch.pid = -1;
+            ch.exited = 1;
+            ch.status = status;
+        } else if (ch.exited && !out_ready && !err_ready &&
+                   ch.remote_window > 0) {
+            /* The child is gone and its output has been forwarded */
+            ch.exited = 0;
+            if (finish(c, &ch) < 0) {
+                ret = -1;
+                break;
+            }
+            close_child_io(&ch);
+        }
+    }
+
+    if (ch.pid > 0) {
+        kill(ch.pid, SIGHUP);
+        waitpid(ch.pid, NULL, 0);
+    }
+    close_child_io(&ch);
+    return ret;
+}
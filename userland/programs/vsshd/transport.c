@@ -0,0 +1,637 @@
+/*
+ * VeridianOS Remote Shell Daemon -- vsshd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * SSH transport layer (RFC 4253): version exchange, binary packets and
+ * curve25519-sha256 key exchange.
+ *
+ * Once keys are in place every packet is sealed with AES-128-GCM as in
+ * RFC 5647: the 4-byte packet length travels in the clear as additional
+ * authenticated data, and the nonce is the 12-byte IV whose last 8 bytes
+ * count packets.
+ */
+
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/syscall.h>
+
+#include "vsshd.h"
+
+/* ========================================================================= */
+/* Buffers                                                                   */
+/* ========================================================================= */
+
+void buf_reset(sshbuf_t *b)
+{
+    b->len = 0;
+    b->pos = 0;
+    b->error = 0;
+}
+
+static int buf_room(sshbuf_t *b, size_t n)
+{
+    if (b->error || n > SSH_BUF_SIZE - b->len) {
+        b->error = 1;
+        return 0;
+    }
+    return 1;
+}
+
+void buf_put_u8(sshbuf_t *b, uint8_t v)
+{
+    if (buf_room(b, 1))
+        b->data[b->len++] = v;
+}
+
+void buf_put_u32(sshbuf_t *b, uint32_t v)
+{
+    if (!buf_room(b, 4))
+        return;
+    b->data[b->len++] = (uint8_t)(v >> 24);
+    b->data[b->len++] = (uint8_t)(v >> 16);
+    b->data[b->len++] = (uint8_t)(v >> 8);
+    b->data[b->len++] = (uint8_t)v;
+}
+
+void buf_put_bytes(sshbuf_t *b, const void *data, size_t len)
+{
+    if (buf_room(b, len)) {
+        memcpy(b->data + b->len, data, len);
+        b->len += len;
+    }
+}
+
+void buf_put_string(sshbuf_t *b, const void *data, size_t len)
+{
+    buf_put_u32(b, (uint32_t)len);
+    buf_put_bytes(b, data, len);
+}
+
+void buf_put_cstring(sshbuf_t *b, const char *s)
+{
+    buf_put_string(b, s, strlen(s));
+}
+
+/* Unsigned big-endian integer as an mpint: no leading zero bytes, plus one
+ * zero byte if the top bit is set. */
+void buf_put_mpint(sshbuf_t *b, const uint8_t *be, size_t len)
+{
+    while (len > 0 && be[0] == 0) {
+        be++;
+        len--;
+    }
+    if (len > 0 && (be[0] & 0x80)) {
+        buf_put_u32(b, (uint32_t)len + 1);
+        buf_put_u8(b, 0);
+        buf_put_bytes(b, be, len);
+    } else {
+        buf_put_string(b, be, len);
+    }
+}
+
+uint8_t buf_get_u8(sshbuf_t *b)
+{
+    if (b->error || b->pos + 1 > b->len) {
+        b->error = 1;
+        return 0;
+    }
+    return b->data[b->pos++];
+}
+
+uint32_t buf_get_u32(sshbuf_t *b)
+{
+    const uint8_t *p;
+
+    if (b->error || b->pos + 4 > b->len) {
+        b->error = 1;
+        return 0;
+    }
+    p = b->data + b->pos;
+    b->pos += 4;
+    return (uint32_t)p[0] << 24 | (uint32_t)p[1] << 16 |
+           (uint32_t)p[2] << 8 | p[3];
+}
+
+const uint8_t *buf_get_string(sshbuf_t *b, uint32_t *len)
+{
+    const uint8_t *p;
+    uint32_t n = buf_get_u32(b);
+
+    if (b->error || n > b->len - b->pos) {
+        b->error = 1;
+        *len = 0;
+        return NULL;
+    }
+    p = b->data + b->pos;
+    b->pos += n;
+    *len = n;
+    return p;
+}
+
+/* A string that must be text: no NUL bytes and shorter than `cap`. */
+int buf_get_cstring(sshbuf_t *b, char *out, size_t cap)
+{
+    uint32_t len;
+    const uint8_t *p = buf_get_string(b, &len);
+
+    if (!p || len >= cap || memchr(p, 0, len)) {
+        b->error = 1;
+        out[0] = '\0';
+        return -1;
+    }
+    memcpy(out, p, len);
+    out[len] = '\0';
+    return 0;
+}
+
+/* ========================================================================= */
+/* Socket I/O                                                                */
+/* ========================================================================= */
+
+static int write_all(int fd, const uint8_t *data, size_t len)
+{
+    while (len > 0) {
+        ssize_t n = write(fd, data, len);
+        if (n < 0 && errno == EINTR)
+            continue;
+        if (n <= 0)
+            return -1;
+        data += n;
+        len -= (size_t)n;
+    }
+    return 0;
+}
+
+static int read_all(int fd, uint8_t *data, size_t len)
+{
+    while (len > 0) {
+        ssize_t n = read(fd, data, len);
+        if (n < 0 && errno == EINTR)
+            continue;
+        if (n <= 0)
+            return -1;
+        data += n;
+        len -= (size_t)n;
+    }
+    return 0;
+}
+
+/* ========================================================================= */
+/* Version exchange                                                          */
+/* ========================================================================= */
+
+#define MAX_BANNER_LINES 32
+
+int transport_version_exchange(ssh_conn_t *c)
+{
+    static const char ours[] = VSSHD_VERSION "\r\n";
+    char *line = c->client_version;
+    size_t cap = sizeof(c->client_version);
+    int lines;
+
+    if (write_all(c->fd, (const uint8_t *)ours, sizeof(ours) - 1) < 0)
+        return -1;
+
+    /* The client may send other lines before its identification string */
+    for (lines = 0; lines < MAX_BANNER_LINES; lines++) {
+        size_t len = 0;
+        uint8_t ch;
+
+        for (;;) {
+            if (read_all(c->fd, &ch, 1) < 0)
+                return -1;
+            if (ch == '\n')
+                break;
+            if (len + 1 >= cap)
+                return -1;
+            line[len++] = (char)ch;
+        }
+        if (len > 0 && line[len - 1] == '\r')
+            len--;
+        line[len] = '\0';
+
+        if (strncmp(line, "SSH-", 4) != 0)
+            continue;
+        if (strncmp(line, "SSH-2.0-", 8) != 0 &&
+            strncmp(line, "SSH-1.99-", 9) != 0) {
+            static const char mismatch[] = "Protocol mismatch.\r\n";
+            write_all(c->fd, (const uint8_t *)mismatch, sizeof(mismatch) - 1);
+            return -1;
+        }
+        return 0;
+    }
+    return -1;
+}
+
+/* ========================================================================= */
+/* Binary packets                                                            */
+/* ========================================================================= */
+
+static uint8_t wire[SSH_BUF_SIZE + 64];
+
+/* Advance the GCM invocation counter (last 8 bytes of the IV). */
+static void iv_increment(uint8_t iv[SSH_GCM_IV_LEN])
+{
+    int i;
+    for (i = SSH_GCM_IV_LEN - 1; i >= 4; i--) {
+        if (++iv[i] != 0)
+            break;
+    }
+}
+
+static long aead_call(long nr, ssh_cipher_t *cipher, const uint8_t *aad,
+                      const uint8_t *in, size_t in_len,
+                      uint8_t *out, size_t out_len)
+{
+    crypto_aead_args_t args = {
+        (size_t)cipher->key, SSH_GCM_KEY_LEN,
+        (size_t)cipher->iv, SSH_GCM_IV_LEN,
+        (size_t)aad, 4,
+        (size_t)in, in_len,
+        (size_t)out, out_len,
+    };
+    long r = veridian_syscall2(nr, CRYPTO_AEAD_AES128_GCM, &args);
+    iv_increment(cipher->iv);
+    return r;
+}
+
+void packet_begin(ssh_conn_t *c, uint8_t type)
+{
+    buf_reset(&c->out);
+    buf_put_u8(&c->out, type);
+}
+
+int packet_send(ssh_conn_t *c)
+{
+    size_t plen = c->out.len;
+    size_t block = c->tx.active ? 16 : 8;
+    /* The length field is outside the padded area once encrypted */
+    size_t covered = c->tx.active ? 1 + plen : 4 + 1 + plen;
+    size_t pad = block - covered % block;
+    uint32_t len;
+
+    if (c->out.error)
+        return -1;
+    if (pad < 4)
+        pad += block;
+    len = (uint32_t)(1 + plen + pad);
+
+    wire[0] = (uint8_t)(len >> 24);
+    wire[1] = (uint8_t)(len >> 16);
+    wire[2] = (uint8_t)(len >> 8);
+    wire[3] = (uint8_t)len;
+    wire[4] = (uint8_t)pad;
+    memcpy(wire + 5, c->out.data, plen);
+    if (getentropy(wire + 5 + plen, pad) < 0)
+        return -1;
+
+    if (c->tx.active) {
+        if (aead_call(SYS_CRYPTO_AEAD_SEAL, &c->tx, wire, wire + 4, len,
+                      wire + 4, len + SSH_GCM_TAG_LEN) < 0)
+            return -1;
+        len += SSH_GCM_TAG_LEN;
+    }
+
+    c->tx_seq++;
+    return write_all(c->fd, wire, 4 + (size_t)len);
+}
+
+/* Read one packet into c->in. Returns the message type, with c->in
+ * positioned just after it, or -1 on error. */
+int packet_read(ssh_conn_t *c)
+{
+    uint32_t len;
+    size_t pad, plen;
+    uint8_t *plain;
+
+    if (read_all(c->fd, wire, 4) < 0)
+        return -1;
+    len = (uint32_t)wire[0] << 24 | (uint32_t)wire[1] << 16 |
+          (uint32_t)wire[2] << 8 | wire[3];
+
+    if (c->rx.active) {
+        if (len < 16 || len % 16 != 0 || len > SSH_MAX_PACKET)
+            return -1;
+        if (read_all(c->fd, wire + 4, len + SSH_GCM_TAG_LEN) < 0)
+            return -1;
+        plain = c->in.data;
+        if (aead_call(SYS_CRYPTO_AEAD_OPEN, &c->rx, wire, wire + 4,
+                      len + SSH_GCM_TAG_LEN, plain, len) != (long)len) {
+            packet_disconnect(c, SSH_DISCONNECT_MAC_ERROR,
+                              "message authentication failed");
+            return -1;
+        }
+    } else {
+        if (len < 8 || len > SSH_MAX_PACKET || (len + 4) % 8 != 0)
+            return -1;
+        plain = wire + 4;
+        if (read_all(c->fd, plain, len) < 0)
+            return -1;
+    }
+
+    pad = plain[0];
+    if (pad < 4 || pad + 2 > len)
+        return -1;
+    plen = len - pad - 1;
+
+    memmove(c->in.data, plain + 1, plen);
+    c->in.len = plen;
+    c->in.pos = 0;
+    c->in.error = 0;
+    c->rx_seq++;
+    return buf_get_u8(&c->in);
+}
+
+void packet_disconnect(ssh_conn_t *c, uint32_t reason, const char *msg)
+{
+    packet_begin(c, SSH_MSG_DISCONNECT);
+    buf_put_u32(&c->out, reason);
+    buf_put_cstring(&c->out, msg);
+    buf_put_cstring(&c->out, "");
+    packet_send(c);
+    fprintf(stderr, "vsshd: %s: disconnecting: %s\n", c->peer, msg);
+}
+
+void packet_unimplemented(ssh_conn_t *c)
+{
+    packet_begin(c, SSH_MSG_UNIMPLEMENTED);
+    buf_put_u32(&c->out, c->rx_seq - 1);
+    packet_send(c);
+}
+
+/*
+ * Read a packet for the layers above the transport. IGNORE, DEBUG and
+ * UNIMPLEMENTED are consumed here and a KEXINIT from the client runs a key
+ * re-exchange; both return 0, as nothing else may be waiting on the
+ * socket. Returns the message type, 0 or -1 on error or DISCONNECT.
+ */
+int packet_next(ssh_conn_t *c)
+{
+    int type = packet_read(c);
+
+    switch (type) {
+    case SSH_MSG_IGNORE:
+    case SSH_MSG_DEBUG:
+    case SSH_MSG_UNIMPLEMENTED:
+        return 0;
+    case SSH_MSG_KEXINIT:
+        return transport_kex(c, 1) < 0 ? -1 : 0;
+    case SSH_MSG_DISCONNECT:
+        return -1;
+    default:
+        return type;
+    }
+}
+
+/* ========================================================================= */
+/* Key exchange                                                              */
+/* ========================================================================= */
+
+/* Whether comma-separated `list` contains `name` */
+static int name_list_has(const uint8_t *list, uint32_t len, const char *name)
+{
+    size_t n = strlen(name);
+    uint32_t i = 0;
+
+    while (i <= len) {
+        uint32_t end = i;
+        while (end < len && list[end] != ',')
+            end++;
+        if (end - i == n && memcmp(list + i, name, n) == 0)
+            return 1;
+        i = end + 1;
+    }
+    return 0;
+}
+
+/* Whether the first entry of `list` is `name` */
+static int name_list_first(const uint8_t *list, uint32_t len, const char *name)
+{
+    size_t n = strlen(name);
+    return len >= n && memcmp(list, name, n) == 0 && (len == n || list[n] == ',');
+}
+
+/* Packet during key exchange; with strict KEX nothing else may arrive */
+static int kex_read(ssh_conn_t *c)
+{
+    for (;;) {
+        int type = packet_read(c);
+        if ((type == SSH_MSG_IGNORE || type == SSH_MSG_DEBUG) && !c->strict_kex)
+            continue;
+        return type == SSH_MSG_DISCONNECT ? -1 : type;
+    }
+}
+
+static void send_kexinit(ssh_conn_t *c)
+{
+    uint8_t cookie[16];
+
+    getentropy(cookie, sizeof(cookie));
+    packet_begin(c, SSH_MSG_KEXINIT);
+    buf_put_bytes(&c->out, cookie, sizeof(cookie));
+    buf_put_cstring(&c->out, SSH_KEX_ALGS);
+    buf_put_cstring(&c->out, SSH_HOSTKEY_ALG);
+    buf_put_cstring(&c->out, SSH_CIPHER_ALG);
+    buf_put_cstring(&c->out, SSH_CIPHER_ALG);
+    buf_put_cstring(&c->out, SSH_MAC_ALG);
+    buf_put_cstring(&c->out, SSH_MAC_ALG);
+    buf_put_cstring(&c->out, SSH_COMP_ALG);
+    buf_put_cstring(&c->out, SSH_COMP_ALG);
+    buf_put_cstring(&c->out, "");
+    buf_put_cstring(&c->out, "");
+    buf_put_u8(&c->out, 0);     /* first_kex_packet_follows */
+    buf_put_u32(&c->out, 0);    /* reserved */
+}
+
+/*
+ * Check the client's KEXINIT (in c->in) against what we offer. Sets
+ * *skip_guess if the client sent a wrongly guessed first KEX packet.
+ */
+static int check_kexinit(ssh_conn_t *c, int *skip_guess)
+{
+    const uint8_t *lists[10];
+    uint32_t lens[10];
+    int i, follows;
+
+    c->in.pos += 16;    /* cookie */
+    for (i = 0; i < 10; i++)
+        lists[i] = buf_get_string(&c->in, &lens[i]);
+    follows = buf_get_u8(&c->in);
+    if (c->in.error)
+        return -1;
+
+    if (!name_list_has(lists[0], lens[0], "curve25519-sha256") &&
+        !name_list_has(lists[0], lens[0], "curve25519-sha256@libssh.org"))
+        return -1;
+    if (!name_list_has(lists[1], lens[1], SSH_HOSTKEY_ALG) ||
+        !name_list_has(lists[2], lens[2], SSH_CIPHER_ALG) ||
+        !name_list_has(lists[3], lens[3], SSH_CIPHER_ALG) ||
+        !name_list_has(lists[6], lens[6], SSH_COMP_ALG) ||
+        !name_list_has(lists[7], lens[7], SSH_COMP_ALG))
+        return -1;
+
+    if (!c->kex_done)
+        c->strict_kex = name_list_has(lists[0], lens[0], SSH_KEX_STRICT_C);
+
+    *skip_guess = follows &&
+        !((name_list_first(lists[0], lens[0], "curve25519-sha256") ||
+           name_list_first(lists[0], lens[0], "curve25519-sha256@libssh.org")) &&
+          name_list_first(lists[1], lens[1], SSH_HOSTKEY_ALG));
+    return 0;
+}
+
+/* HASH(K || H || letter || session_id), truncated to `len` bytes */
+static void derive_key(const uint8_t k[32], const uint8_t h[32], char letter,
+                       const uint8_t *session_id, uint8_t *out, size_t len)
+{
+    static sshbuf_t b;
+    uint8_t digest[32];
+
+    buf_reset(&b);
+    buf_put_mpint(&b, k, 32);
+    buf_put_bytes(&b, h, 32);
+    buf_put_u8(&b, (uint8_t)letter);
+    buf_put_bytes(&b, session_id, 32);
+    crypto_sha256(b.data, b.len, digest);
+    memcpy(out, digest, len);
+    memset(digest, 0, sizeof(digest));
+    memset(b.data, 0, b.len);
+}
+
+/*
+ * Run a key exchange. `have_peer_kexinit` is set when the client started a
+ * re-exchange and its KEXINIT is already in c->in.
+ */
+int transport_kex(ssh_conn_t *c, int have_peer_kexinit)
+{
+    static sshbuf_t i_c, i_s, hash;
+    static const uint8_t basepoint[32] = { 9 };
+    uint8_t scalar[32], q_s[32], q_c[32], k[32], h[32], sig[64];
+    ssh_cipher_t tx, rx;
+    const uint8_t *p;
+    uint32_t len;
+    int type, skip_guess, ret = -1;
+
+    send_kexinit(c);
+    memcpy(i_s.data, c->out.data, c->out.len);
+    i_s.len = c->out.len;
+    if (packet_send(c) < 0)
+        return -1;
+
+    if (!have_peer_kexinit) {
+        type = kex_read(c);
+        if (type != SSH_MSG_KEXINIT) {
+            packet_disconnect(c, SSH_DISCONNECT_PROTOCOL_ERROR,
+                              "expected KEXINIT");
+            return -1;
+        }
+    }
+    memcpy(i_c.data, c->in.data, c->in.len);
+    i_c.len = c->in.len;
+
+    if (check_kexinit(c, &skip_guess) < 0) {
+        packet_disconnect(c, SSH_DISCONNECT_KEY_EXCHANGE_FAILED,
+                          "no matching algorithms");
+        return -1;
+    }
+    if (!c->kex_done && c->strict_kex && c->rx_seq != 1) {
+        packet_disconnect(c, SSH_DISCONNECT_PROTOCOL_ERROR,
+                          "strict KEX: KEXINIT was not the first packet");
+        return -1;
+    }
+    if (skip_guess && kex_read(c) < 0)
+        return -1;
+
+    type = kex_read(c);
+    p = buf_get_string(&c->in, &len);
+    if (type != SSH_MSG_KEX_ECDH_INIT || !p || len != 32) {
+        packet_disconnect(c, SSH_DISCONNECT_KEY_EXCHANGE_FAILED,
+                          "expected KEX_ECDH_INIT");
+        return -1;
+    }
+    memcpy(q_c, p, 32);
+
+    if (getentropy(scalar, sizeof(scalar)) < 0 ||
+        veridian_syscall3(SYS_CRYPTO_X25519, scalar, basepoint, q_s) < 0 ||
+        veridian_syscall3(SYS_CRYPTO_X25519, scalar, q_c, k) < 0) {
+        packet_disconnect(c, SSH_DISCONNECT_KEY_EXCHANGE_FAILED,
+                          "key agreement failed");
+        goto out;
+    }
+
+    /* Exchange hash, RFC 8731 section 3 */
+    buf_reset(&hash);
+    buf_put_cstring(&hash, c->client_version);
+    buf_put_cstring(&hash, VSSHD_VERSION);
+    buf_put_string(&hash, i_c.data, i_c.len);
+    buf_put_string(&hash, i_s.data, i_s.len);
+    buf_put_u32(&hash, 4 + 11 + 4 + 32);     /* K_S */
+    buf_put_cstring(&hash, SSH_HOSTKEY_ALG);
+    buf_put_string(&hash, c->host_pub, 32);
+    buf_put_string(&hash, q_c, 32);
+    buf_put_string(&hash, q_s, 32);
+    buf_put_mpint(&hash, k, 32);
+    if (hash.error || crypto_sha256(hash.data, hash.len, h) < 0)
+        goto out;
+    if (!c->kex_done)
+        memcpy(c->session_id, h, 32);
+
+    {
+        crypto_sign_args_t args = {
+            (size_t)c->host_seed, 32, (size_t)h, 32,
+            (size_t)sig, sizeof(sig), 0, 0,
+        };
+        if (veridian_syscall2(SYS_CRYPTO_SIGN, CRYPTO_SIG_ED25519, &args) != 64)
+            goto out;
+    }
+
+    packet_begin(c, SSH_MSG_KEX_ECDH_REPLY);
+    buf_put_u32(&c->out, 4 + 11 + 4 + 32);
+    buf_put_cstring(&c->out, SSH_HOSTKEY_ALG);
+    buf_put_string(&c->out, c->host_pub, 32);
+    buf_put_string(&c->out, q_s, 32);
+    buf_put_u32(&c->out, 4 + 11 + 4 + 64);
+    buf_put_cstring(&c->out, SSH_HOSTKEY_ALG);
+    buf_put_string(&c->out, sig, 64);
+    if (packet_send(c) < 0)
+        goto out;
+
+    /* Client to server: IV 'A', key 'C'; server to client: 'B', 'D' */
+    memset(&tx, 0, sizeof(tx));
+    memset(&rx, 0, sizeof(rx));
+    derive_key(k, h, 'A', c->session_id, rx.iv, SSH_GCM_IV_LEN);
+    derive_key(k, h, 'B', c->session_id, tx.iv, SSH_GCM_IV_LEN);
+    derive_key(k, h, 'C', c->session_id, rx.key, SSH_GCM_KEY_LEN);
+    derive_key(k, h, 'D', c->session_id, tx.key, SSH_GCM_KEY_LEN);
+    rx.active = tx.active = 1;
+
+    packet_begin(c, SSH_MSG_NEWKEYS);
+    if (packet_send(c) < 0)
+        goto out;
+    c->tx = tx;
+    if (c->strict_kex)
+        c->tx_seq = 0;
+
+    if (kex_read(c) != SSH_MSG_NEWKEYS) {
+        packet_disconnect(c, SSH_DISCONNECT_PROTOCOL_ERROR, "expected NEWKEYS");
+        goto out;
+    }
+    c->rx = rx;
+    if (c->strict_kex)
+        c->rx_seq = 0;
+
+    c->kex_done = 1;
+    ret = 0;
+
+out:
+    memset(scalar, 0, sizeof(scalar));
+    memset(k, 0, sizeof(k));
+    memset(&tx, 0, sizeof(tx));
+    memset(&rx, 0, sizeof(rx));
+    memset(hash.data, 0, hash.len);
+    return ret;
+}
@@ -0,0 +1,219 @@
+/*
+ * VeridianOS Remote Shell Daemon -- vsshd
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Shared types and function declarations for the vsshd service.
+ */
+
+#ifndef VSSHD_H
+#define VSSHD_H
+
+#include <stdint.h>
+#include <stddef.h>
+#include <sys/types.h>
+
+/* ========================================================================= */
+/* Configuration                                                             */
+/* ========================================================================= */
+
+#define VSSHD_PORT          22
+#define VSSHD_VERSION       "SSH-2.0-VeridianOS_vsshd_1.0"
+#define VSSHD_KEY_DIR       "/etc/vsshd"
+#define VSSHD_HOST_KEY      "/etc/vsshd/ssh_host_ed25519_key"
+#define VSSHD_HOST_PUB      "/etc/vsshd/ssh_host_ed25519_key.pub"
+#define VSSHD_AUTH_KEYS     ".ssh/authorized_keys"
+
+#define VSSHD_MAX_AUTH      6       /* authentication attempts per connection */
+#define VSSHD_LOGIN_GRACE   120     /* seconds to authenticate */
+
+/* ========================================================================= */
+/* Protocol constants (RFC 4250-4254, RFC 8731)                              */
+/* ========================================================================= */
+
+#define SSH_MSG_DISCONNECT                  1
+#define SSH_MSG_IGNORE                      2
+#define SSH_MSG_UNIMPLEMENTED               3
+#define SSH_MSG_DEBUG                       4
+#define SSH_MSG_SERVICE_REQUEST             5
+#define SSH_MSG_SERVICE_ACCEPT              6
+#define SSH_MSG_KEXINIT                     20
+#define SSH_MSG_NEWKEYS                     21
+#define SSH_MSG_KEX_ECDH_INIT               30
+#define SSH_MSG_KEX_ECDH_REPLY              31
+#define SSH_MSG_USERAUTH_REQUEST            50
+#define SSH_MSG_USERAUTH_FAILURE            51
+#define SSH_MSG_USERAUTH_SUCCESS            52
+#define SSH_MSG_USERAUTH_PK_OK              60
+#define SSH_MSG_GLOBAL_REQUEST              80
+#define SSH_MSG_REQUEST_FAILURE             82
+#define SSH_MSG_CHANNEL_OPEN                90
+#define SSH_MSG_CHANNEL_OPEN_CONFIRMATION   91
+#define SSH_MSG_CHANNEL_OPEN_FAILURE        92
+#define SSH_MSG_CHANNEL_WINDOW_ADJUST       93
+#define SSH_MSG_CHANNEL_DATA                94
+#define SSH_MSG_CHANNEL_EXTENDED_DATA       95
+#define SSH_MSG_CHANNEL_EOF                 96
+#define SSH_MSG_CHANNEL_CLOSE               97
+#define SSH_MSG_CHANNEL_REQUEST             98
+#define SSH_MSG_CHANNEL_SUCCESS             99
+#define SSH_MSG_CHANNEL_FAILURE             100
+
+#define SSH_DISCONNECT_PROTOCOL_ERROR           2
+#define SSH_DISCONNECT_KEY_EXCHANGE_FAILED      3
+#define SSH_DISCONNECT_MAC_ERROR                5
+#define SSH_DISCONNECT_SERVICE_NOT_AVAILABLE    7
+#define SSH_DISCONNECT_BY_APPLICATION           11
+#define SSH_DISCONNECT_NO_MORE_AUTH_METHODS     14
+
+#define SSH_OPEN_ADMINISTRATIVELY_PROHIBITED    1
+#define SSH_OPEN_UNKNOWN_CHANNEL_TYPE           3
+
+#define SSH_EXTENDED_DATA_STDERR            1
+
+/* Algorithms offered in KEXINIT; each is the only one of its kind */
+#define SSH_KEX_ALGS        "curve25519-sha256,curve25519-sha256@libssh.org," \
+                            "kex-strict-s-v00@openssh.com"
+#define SSH_KEX_STRICT_C    "kex-strict-c-v00@openssh.com"
+#define SSH_HOSTKEY_ALG     "ssh-ed25519"
+#define SSH_CIPHER_ALG      "aes128-gcm@openssh.com"
+#define SSH_MAC_ALG         "hmac-sha2-256"     /* unused under GCM */
+#define SSH_COMP_ALG        "none"
+
+/* ========================================================================= */
+/* Kernel crypto interface (mirrors kernel/src/syscall/crypto.rs)            */
+/* ========================================================================= */
+
+#define CRYPTO_HASH_SHA256      1
+#define CRYPTO_AEAD_AES128_GCM  1
+#define CRYPTO_SIG_ED25519      0x0807
+
+typedef struct {
+    size_t key, key_len;
+    size_t nonce, nonce_len;
+    size_t aad, aad_len;
+    size_t input, input_len;
+    size_t output, output_len;
+} crypto_aead_args_t;
+
+typedef struct {
+    size_t key, key_len;
+    size_t message, message_len;
+    size_t signature, signature_len;
+} crypto_verify_args_t;
+
+typedef struct {
+    size_t key, key_len;
+    size_t message, message_len;
+    size_t signature, signature_len;
+    size_t public_key, public_key_len;
+} crypto_sign_args_t;
+
+/* ========================================================================= */
+/* Limits                                                                    */
+/* ========================================================================= */
+
+#define SSH_MAX_PACKET      35000   /* RFC 4253 6.1 minimum we must accept */
+#define SSH_MAX_PAYLOAD     32768
+#define SSH_BUF_SIZE        (SSH_MAX_PACKET + 64)
+#define SSH_CHANNEL_WINDOW  (256 * 1024)
+#define SSH_CHANNEL_MAXPKT  SSH_MAX_PAYLOAD
+
+#define SSH_GCM_TAG_LEN     16
+#define SSH_GCM_IV_LEN      12
+#define SSH_GCM_KEY_LEN     16
+
+/* ========================================================================= */
+/* Buffers                                                                   */
+/* ========================================================================= */
+
+/*
+ * Byte buffer used both to build and to parse packets. Writes past the end
+ * and reads past `len` set `error` instead of failing individually, so a
+ * message is built or parsed in one go and checked once.
+ */
+typedef struct {
+    uint8_t data[SSH_BUF_SIZE];
+    size_t  len;
+    size_t  pos;
+    int     error;
+} sshbuf_t;
+
+void buf_reset(sshbuf_t *b);
+void buf_put_u8(sshbuf_t *b, uint8_t v);
+void buf_put_u32(sshbuf_t *b, uint32_t v);
+void buf_put_bytes(sshbuf_t *b, const void *data, size_t len);
+void buf_put_string(sshbuf_t *b, const void *data, size_t len);
+void buf_put_cstring(sshbuf_t *b, const char *s);
+void buf_put_mpint(sshbuf_t *b, const uint8_t *be, size_t len);
+
+uint8_t        buf_get_u8(sshbuf_t *b);
+uint32_t       buf_get_u32(sshbuf_t *b);
+const uint8_t *buf_get_string(sshbuf_t *b, uint32_t *len);
+int            buf_get_cstring(sshbuf_t *b, char *out, size_t cap);
+
+/* ========================================================================= */
+/* Connection state                                                          */
+/* ========================================================================= */
+
+typedef struct {
+    int     active;
+    uint8_t key[SSH_GCM_KEY_LEN];
+    uint8_t iv[SSH_GCM_IV_LEN];     /* fixed field + invocation counter */
+} ssh_cipher_t;
+
+typedef struct {
+    int          fd;
+    char         peer[64];              /* "a.b.c.d port" */
+    char         local[64];
+    char         client_version[256];
+
+    ssh_cipher_t tx, rx;
+    uint32_t     tx_seq, rx_seq;
+    int          strict_kex;
+    int          kex_done;
+
+    uint8_t      session_id[32];
+    uint8_t      host_seed[32];
+    uint8_t      host_pub[32];
+
+    sshbuf_t     in;                    /* last received payload */
+    sshbuf_t     out;                   /* payload being built */
+} ssh_conn_t;
+
+/* Authenticated user, filled in by auth.c */
+typedef struct {
+    char  name[64];
+    uid_t uid;
+    gid_t gid;
+    char  home[256];
+    char  shell[256];
+} ssh_user_t;
+
+/* ========================================================================= */
+/* Function declarations                                                     */
+/* ========================================================================= */
+
+/* util (main.c) */
+int    crypto_sha256(const void *data, size_t len, uint8_t out[32]);
+size_t base64_encode(const uint8_t *in, size_t len, char *out, size_t cap);
+long   base64_decode(const char *in, size_t len, uint8_t *out, size_t cap);
+
+/* transport.c */
+int  transport_version_exchange(ssh_conn_t *c);
+int  transport_kex(ssh_conn_t *c, int have_peer_kexinit);
+void packet_begin(ssh_conn_t *c, uint8_t type);
+int  packet_send(ssh_conn_t *c);
+int  packet_read(ssh_conn_t *c);
+int  packet_next(ssh_conn_t *c);
+void packet_disconnect(ssh_conn_t *c, uint32_t reason, const char *msg);
+void packet_unimplemented(ssh_conn_t *c);
+
+/* auth.c */
+int auth_user(ssh_conn_t *c, ssh_user_t *user);
+
+/* session.c */
+int session_run(ssh_conn_t *c, const ssh_user_t *user);
+
+#endif /* VSSHD_H */
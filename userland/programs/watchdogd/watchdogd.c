@@ -0,0 +1,335 @@
+/*
+ * watchdogd -- pet the kernel watchdog while the system is healthy
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Arms watchdog channel WATCHDOGD_CHANNEL (see SYS_WATCHDOG in
+ * <veridian/syscall.h>) and runs its health checks every interval. While
+ * they pass the channel is pet; after `retries` failed rounds in a row it
+ * is left to run out and the kernel resets the machine. A round that
+ * passes again resumes petting. SIGTERM and SIGINT disarm the channel
+ * before exiting, so stopping the unit does not reboot the machine.
+ *
+ * Policy comes from /etc/watchdog.toml (flat `key = value` lines; a missing
+ * file means the defaults, which only check that the watchdog is pet):
+ *
+ *   interval_ms = 10000      time between rounds
+ *   timeout_ms = 60000       watchdog timeout, longer than interval_ms
+ *   retries = 3              failed rounds tolerated before giving up
+ *   min_free_kb = 4096       MemAvailable in /proc/meminfo at least this
+ *   max_procs = 512          processes in /proc/loadavg at most this
+ *   writable = "/tmp"        a file can be created and written here
+ *   units = ["pkgd"]         these init units are running and not failing
+ *
+ * Usage:
+ *   watchdogd [config]
+ */
+
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <veridian/init.h>
+#include <veridian/syscall.h>
+
+#define DEFAULT_CONFIG      "/etc/watchdog.toml"
+#define WATCHDOGD_CHANNEL   1
+#define MAX_UNITS           16
+
+static struct {
+    long interval_ms;
+    long timeout_ms;
+    long retries;
+    long min_free_kb;       /* 0: not checked */
+    long max_procs;         /* 0: not checked */
+    char writable[128];     /* "": not checked */
+    char units[MAX_UNITS][INIT_NAME_MAX + 1];
+    int nunits;
+} cfg = { 10000, 60000, 3, 0, 0, "", { { 0 } }, 0 };
+
+static volatile sig_atomic_t stop;
+
+static void on_signal(int sig)
+{
+    (void)sig;
+    stop = 1;
+}
+
+static long watchdog(long op, long arg)
+{
+    return veridian_syscall2(SYS_WATCHDOG, op | WATCHDOG_CHANNEL(WATCHDOGD_CHANNEL), arg);
+}
+
+/* ========================================================================= */
+/* Configuration                                                             */
+/* ========================================================================= */
+
+static char *trim(char *s)
+{
+    char *end;
+
+    while (*s == ' ' || *s == '\t')
+        s++;
+    end = s + strlen(s);
+    while (end > s && (end[-1] == ' ' || end[-1] == '\t' || end[-1] == '\r'))
+        *--end = '\0';
+    return s;
+}
+
+/* Copy the quoted string at `s` into `out`; returns 0 on success. */
+static int parse_string(const char *s, char *out, size_t size)
+{
+    const char *end;
+
+    if (*s != '"' || !(end = strchr(s + 1, '"')) || (size_t)(end - s - 1) >= size)
+        return -1;
+    memcpy(out, s + 1, (size_t)(end - s - 1));
+    out[end - s - 1] = '\0';
+    return 0;
+}
+
+/* Parse `["a", "b"]` into cfg.units. */
+static int parse_units(char *s)
+{
+    if (*s++ != '[')
+        return -1;
+    cfg.nunits = 0;
+    for (;;) {
+        s = trim(s);
+        if (*s == ']')
+            return 0;
+        if (cfg.nunits == MAX_UNITS ||
+            parse_string(s, cfg.units[cfg.nunits], sizeof(cfg.units[0])) < 0)
+            return -1;
+        cfg.nunits++;
+        s = trim(strchr(s + 1, '"') + 1);
+        if (*s == ',')
+            s++;
+        else if (*s != ']')
+            return -1;
+    }
+}
+
+static int load_config(const char *path)
+{
+    char line[512];
+    int lineno = 0;
+    FILE *f = fopen(path, "r");
+
+    if (!f)
+        return errno == ENOENT ? 0 : -1;
+    while (fgets(line, sizeof(line), f)) {
+        char *key, *value, *eq;
+        int ok;
+
+        lineno++;
+        line[strcspn(line, "#\n")] = '\0';
+        key = trim(line);
+        if (!*key)
+            continue;
+        if (!(eq = strchr(key, '='))) {
+            fprintf(stderr, "watchdogd: %s:%d: expected key = value\n", path, lineno);
+            fclose(f);
+            return -1;
+        }
+        *eq = '\0';
+        key = trim(key);
+        value = trim(eq + 1);
+
+        if (strcmp(key, "interval_ms") == 0)
+            ok = (cfg.interval_ms = atol(value)) > 0;
+        else if (strcmp(key, "timeout_ms") == 0)
+            ok = (cfg.timeout_ms = atol(value)) > 0;
+        else if (strcmp(key, "retries") == 0)
+            ok = (cfg.retries = atol(value)) > 0;
+        else if (strcmp(key, "min_free_kb") == 0)
+            ok = (cfg.min_free_kb = atol(value)) >= 0;
+        else if (strcmp(key, "max_procs") == 0)
+            ok = (cfg.max_procs = atol(value)) >= 0;
+        else if (strcmp(key, "writable") == 0)
+            ok = parse_string(value, cfg.writable, sizeof(cfg.writable)) == 0;
+        else if (strcmp(key, "units") == 0)
+            ok = parse_units(value) == 0;
+        else
+            ok = 0;
+        if (!ok) {
+            fprintf(stderr, "watchdogd: %s:%d: bad %s\n", path, lineno, key);
+            fclose(f);
+            return -1;
+        }
+    }
+    fclose(f);
+
+    if (cfg.timeout_ms < 1000 || cfg.timeout_ms <= cfg.interval_ms) {
+        fprintf(stderr, "watchdogd: %s: timeout_ms must be at least 1000 and longer than "
+                        "interval_ms\n", path);
+        return -1;
+    }
+    return 0;
+}
+
+/* ========================================================================= */
+/* Health checks                                                             */
+/* ========================================================================= */
+
+/* Each check returns NULL when healthy, otherwise why not. */
+
+static const char *check_memory(void)
+{
+    char line[128];
+    long kb = -1;
+    FILE *f;
+
+    if (!cfg.min_free_kb)
+        return NULL;
+    if (!(f = fopen("/proc/meminfo", "r")))
+        return "cannot read /proc/meminfo";
+    while (fgets(line, sizeof(line), f))
+        if (sscanf(line, "MemAvailable: %ld kB", &kb) == 1)
+            break;
+    fclose(f);
+    return kb >= 0 && kb < cfg.min_free_kb ? "available memory below min_free_kb" : NULL;
+}
+
+static const char *check_procs(void)
+{
+    long running, total;
+    FILE *f;
+    int n;
+
+    if (!cfg.max_procs)
+        return NULL;
+    if (!(f = fopen("/proc/loadavg", "r")))
+        return "cannot read /proc/loadavg";
+    n = fscanf(f, "%*s %*s %*s %ld/%ld", &running, &total);
+    fclose(f);
+    if (n != 2)
+        return "cannot parse /proc/loadavg";
+    return total > cfg.max_procs ? "more processes than max_procs" : NULL;
+}
+
+static const char *check_writable(void)
+{
+    char path[sizeof(cfg.writable) + 32];
+    int fd, ok;
+
+    if (!cfg.writable[0])
+        return NULL;
+    snprintf(path, sizeof(path), "%s/.watchdogd.%d", cfg.writable, (int)getpid());
+    if ((fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0600)) < 0)
+        return "cannot create a file in the writable directory";
+    ok = write(fd, "ok\n", 3) == 3;
+    close(fd);
+    unlink(path);
+    return ok ? NULL : "cannot write to the writable directory";
+}
+
+/* Status table columns used here (see INIT_STATUS_FILE) */
+enum { F_NAME, F_STATE, F_PID, F_RESTARTS, F_HEALTH };
+
+static const char *check_units(void)
+{
+    static char why[64];
+    char line[512];
+    int ok[MAX_UNITS] = { 0 };
+    FILE *f;
+
+    if (!cfg.nunits)
+        return NULL;
+    if (!(f = fopen(INIT_STATUS_FILE, "r")))
+        return "cannot read " INIT_STATUS_FILE;
+    while (fgets(line, sizeof(line), f)) {
+        char *field[F_HEALTH + 1] = { 0 };
+        char *p = line;
+
+        for (int i = 0; i <= F_HEALTH && p; i++) {
+            field[i] = p;
+            p = strchr(p, '\t');
+            if (p)
+                *p++ = '\0';
+        }
+        if (!field[F_HEALTH])
+            continue;
+        for (int i = 0; i < cfg.nunits; i++)
+            if (strcmp(field[F_NAME], cfg.units[i]) == 0)
+                ok[i] = strcmp(field[F_STATE], "running") == 0 &&
+                        strcmp(field[F_HEALTH], "failed") != 0;
+    }
+    fclose(f);
+
+    for (int i = 0; i < cfg.nunits; i++) {
+        if (!ok[i]) {
+            snprintf(why, sizeof(why), "unit %s is not running or failing", cfg.units[i]);
+            return why;
+        }
+    }
+    return NULL;
+}
+
+static const char *check_health(void)
+{
+    const char *(*const checks[])(void) = {
+        check_memory, check_procs, check_writable, check_units,
+    };
+
+    for (size_t i = 0; i < sizeof(checks) / sizeof(checks[0]); i++) {
+        const char *why = checks[i]();
+        if (why)
+            return why;
+    }
+    return NULL;
+}
+
+/* ========================================================================= */
+/* Main loop                                                                 */
+/* ========================================================================= */
+
+int main(int argc, char **argv)
+{
+    const char *config = argc > 1 ? argv[1] : DEFAULT_CONFIG;
+    long failures = 0, ret;
+
+    if (argc > 2) {
+        fprintf(stderr, "usage: watchdogd [config]\n");
+        return 1;
+    }
+    if (load_config(config) < 0)
+        return 1;
+
+    ret = watchdog(WATCHDOG_ARM, cfg.timeout_ms);
+    if (ret < 0) {
+        fprintf(stderr, "watchdogd: cannot arm the watchdog: %s\n", strerror((int)-ret));
+        return 1;
+    }
+    signal(SIGTERM, on_signal);
+    signal(SIGINT, on_signal);
+    printf("[watchdogd] channel %d armed, %ld ms timeout, checking every %ld ms\n",
+           WATCHDOGD_CHANNEL, cfg.timeout_ms, cfg.interval_ms);
+
+    while (!stop) {
+        const char *why = check_health();
+
+        if (!why) {
+            if (failures > cfg.retries)
+                printf("[watchdogd] healthy again, petting resumed\n");
+            failures = 0;
+        } else if (++failures <= cfg.retries) {
+            printf("[watchdogd] health check failed (%ld/%ld): %s\n", failures, cfg.retries,
+                   why);
+        } else if (failures == cfg.retries + 1) {
+            printf("[watchdogd] %s; no longer petting, reset in %ld ms\n", why,
+                   cfg.timeout_ms);
+        }
+        if (failures <= cfg.retries)
+            watchdog(WATCHDOG_PET, 0);
+        usleep((unsigned int)cfg.interval_ms * 1000);
+    }
+
+    watchdog(WATCHDOG_DISARM, 0);
+    printf("[watchdogd] stopped, channel %d disarmed\n", WATCHDOGD_CHANNEL);
+    return 0;
+}
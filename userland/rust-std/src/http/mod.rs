@@ -0,0 +1,457 @@
+//! HTTP/1.1 client.
+//!
+//! A small blocking client over [`TcpStream`] for fetching packages and
+//! similar one-shot transfers:
+//!
+//! - `GET`, `HEAD`, `POST`, `PUT` and `DELETE` requests
+//! - `Content-Length`, chunked and close-delimited response bodies
+//! - redirect following (301/302/303/307/308) up to a configurable limit
+//! - an overall per-request timeout
+//! - `https://` over the TLS 1.3 client in [`crate::tls`], verified against the
+//!   bundled roots unless [`Client::with_root_store`] says otherwise
+//!
+//! Each request uses its own connection (`Connection: close`).
+//!
+//! ```rust,no_run
+//! use veridian_std::{http::Client, platform::time::Duration};
+//!
+//! let client = Client::new().with_timeout(Duration::from_secs(30));
+//! let resp = client.get("http://10.0.2.2:8000/hello.vpk").unwrap();
+//! assert!(resp.is_success());
+//! ```
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    sys::veridian::{
+        net::{IpAddr, SocketAddr, TcpStream},
+        time::{Duration, Instant},
+        SyscallError,
+    },
+    tls::{self, ClientConfig, RootStore, TlsStream},
+};
+
+pub mod resolve;
+pub mod url;
+mod wire;
+
+pub use url::{Scheme, Url};
+
+/// Redirects followed before giving up.
+pub const DEFAULT_MAX_REDIRECTS: u32 = 5;
+/// Largest response body buffered by default (64 MiB).
+pub const DEFAULT_MAX_BODY: usize = 64 * 1024 * 1024;
+/// `User-Agent` sent unless the request overrides it.
+pub const DEFAULT_USER_AGENT: &str = "veridian-http/0.1";
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// HTTP client errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Socket or file error from the kernel.
+    Io(SyscallError),
+    /// The URL could not be parsed.
+    InvalidUrl,
+    /// Scheme other than `http` or `https`.
+    UnsupportedScheme,
+    /// The host name did not resolve.
+    HostNotFound,
+    /// The request did not complete within the client timeout.
+    TimedOut,
+    /// The server sent something that is not valid HTTP/1.x.
+    Malformed(&'static str),
+    /// A redirect chain exceeded the client limit.
+    TooManyRedirects,
+    /// The response body exceeded the client limit.
+    BodyTooLarge,
+    /// A request header name or value contains forbidden bytes.
+    InvalidHeader,
+    /// The TLS handshake failed or the server could not be verified.
+    Tls(tls::Error),
+}
+
+impl From<SyscallError> for Error {
+    fn from(e: SyscallError) -> Self {
+        match e {
+            SyscallError::TimedOut => Error::TimedOut,
+            e => Error::Io(e),
+        }
+    }
+}
+
+impl From<tls::Error> for Error {
+    fn from(e: tls::Error) -> Self {
+        match e {
+            tls::Error::Io(e) => Error::from(e),
+            tls::Error::TimedOut => Error::TimedOut,
+            e => Error::Tls(e),
+        }
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::InvalidUrl => f.write_str("invalid URL"),
+            Error::UnsupportedScheme => f.write_str("unsupported URL scheme"),
+            Error::HostNotFound => f.write_str("host not found"),
+            Error::TimedOut => f.write_str("request timed out"),
+            Error::Malformed(what) => write!(f, "malformed response: {}", what),
+            Error::TooManyRedirects => f.write_str("too many redirects"),
+            Error::BodyTooLarge => f.write_str("response body too large"),
+            Error::InvalidHeader => f.write_str("invalid request header"),
+            Error::Tls(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// ============================================================================
+// Transport
+// ============================================================================
+
+/// Byte stream an HTTP exchange runs over.
+pub trait Transport {
+    /// Read into `buf`, returning 0 at end of stream.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, SyscallError>;
+    /// Write all of `data`.
+    fn write_all(&mut self, data: &[u8]) -> Result<(), SyscallError>;
+}
+
+impl Transport for TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, SyscallError> {
+        TcpStream::read(self, buf)
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> Result<(), SyscallError> {
+        TcpStream::write_all(self, data)
+    }
+}
+
+// ============================================================================
+// Request / Response
+// ============================================================================
+
+/// HTTP request method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+}
+
+impl Method {
+    /// Method name as sent on the request line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+/// An outgoing request.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Create a request with no extra headers and an empty body.
+    pub fn new(method: Method, url: &str) -> Self {
+        Request {
+            method,
+            url: String::from(url),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Add a header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// Set the body.
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    pub(crate) fn header_value(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// A complete response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// Status code, e.g. 200.
+    pub status: u16,
+    /// Reason phrase, e.g. `OK`.
+    pub reason: String,
+    /// Header fields in the order received.
+    pub headers: Vec<(String, String)>,
+    /// Decoded body (chunked framing removed).
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// First value of header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Is the status 2xx?
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Is the status one of the redirects the client follows?
+    pub fn is_redirect(&self) -> bool {
+        matches!(self.status, 301 | 302 | 303 | 307 | 308)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+// ============================================================================
+// Client
+// ============================================================================
+
+/// Blocking HTTP/1.1 client.
+#[derive(Debug, Clone)]
+pub struct Client {
+    timeout: Option<Duration>,
+    max_redirects: u32,
+    max_body: usize,
+    user_agent: &'static str,
+    /// Roots for `https`; `None` loads the bundled set per connection.
+    roots: Option<RootStore>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Client {
+    /// Client with no timeout, 5 redirects and a 64 MiB body limit.
+    pub const fn new() -> Self {
+        Client {
+            timeout: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            max_body: DEFAULT_MAX_BODY,
+            user_agent: DEFAULT_USER_AGENT,
+            roots: None,
+        }
+    }
+
+    /// Fail requests (including redirects) that take longer than `timeout`.
+    ///
+    /// The remaining time is also applied as the socket's receive and send
+    /// timeout; `connect` itself is bounded only by the TCP stack.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Follow at most `max` redirects; 0 returns 3xx responses as-is.
+    pub fn with_max_redirects(mut self, max: u32) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Reject response bodies larger than `max` bytes.
+    pub fn with_max_body_size(mut self, max: usize) -> Self {
+        self.max_body = max;
+        self
+    }
+
+    /// Use `agent` as the default `User-Agent`.
+    pub fn with_user_agent(mut self, agent: &'static str) -> Self {
+        self.user_agent = agent;
+        self
+    }
+
+    /// Verify `https` servers against `roots` instead of the bundled set.
+    pub fn with_root_store(mut self, roots: RootStore) -> Self {
+        self.roots = Some(roots);
+        self
+    }
+
+    /// `GET url`.
+    pub fn get(&self, url: &str) -> Result<Response, Error> {
+        self.send(Request::new(Method::Get, url))
+    }
+
+    /// `POST url` with `body` of type `content_type`.
+    pub fn post(&self, url: &str, content_type: &str, body: &[u8]) -> Result<Response, Error> {
+        self.send(
+            Request::new(Method::Post, url)
+                .header("Content-Type", content_type)
+                .body(body.to_vec()),
+        )
+    }
+
+    /// Send `req`, following redirects.
+    pub fn send(&self, mut req: Request) -> Result<Response, Error> {
+        let deadline = match self.timeout {
+            Some(timeout) => Some(Instant::now().checked_add(timeout).ok_or(Error::TimedOut)?),
+            None => None,
+        };
+
+        let mut url = Url::parse(&req.url)?;
+        let mut redirects = 0;
+        loop {
+            let addr = resolve::lookup_host(url.host())?;
+            let mut stream = TcpStream::connect(&SocketAddr::new(IpAddr::V4(addr), url.port()))?;
+            let mut remaining = None;
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(Error::TimedOut);
+                }
+                let _ = stream.set_read_timeout(Some(left));
+                let _ = stream.set_write_timeout(Some(left));
+                remaining = Some(left);
+            }
+            let response = match url.scheme() {
+                Scheme::Http => self.exchange(&mut stream, &req, &url, deadline)?,
+                Scheme::Https => {
+                    let config = self.tls_config(remaining)?;
+                    let mut tls = TlsStream::connect(stream, url.host(), &config)?;
+                    let response = self.exchange(&mut tls, &req, &url, deadline)?;
+                    let _ = tls.close();
+                    response
+                }
+            };
+
+            let location = match response.header("Location") {
+                Some(location) if response.is_redirect() => location,
+                _ => return Ok(response),
+            };
+            if redirects == self.max_redirects {
+                return if self.max_redirects == 0 {
+                    Ok(response)
+                } else {
+                    Err(Error::TooManyRedirects)
+                };
+            }
+            redirects += 1;
+
+            url = url.join(location)?;
+            req.url = alloc::format!("{}", url);
+            redirect_request(&mut req, response.status);
+        }
+    }
+
+    /// TLS settings for one `https` connection: this client's roots, ALPN
+    /// `http/1.1`, and the handshake bounded by what is left of the request
+    /// timeout.
+    fn tls_config(&self, remaining: Option<Duration>) -> Result<ClientConfig, Error> {
+        let roots = match &self.roots {
+            Some(roots) => roots.clone(),
+            None => RootStore::bundled()?,
+        };
+        Ok(ClientConfig::new(roots)
+            .with_alpn(&[b"http/1.1"])
+            .with_handshake_timeout(remaining.or(Some(tls::DEFAULT_HANDSHAKE_TIMEOUT))))
+    }
+
+    /// Run one request/response exchange over `transport`.
+    pub fn exchange<T: Transport + ?Sized>(
+        &self,
+        transport: &mut T,
+        req: &Request,
+        url: &Url,
+        deadline: Option<Instant>,
+    ) -> Result<Response, Error> {
+        let raw = wire::encode_request(req, url, self.user_agent)?;
+        transport.write_all(&raw)?;
+        let mut reader = wire::Reader::new(transport, deadline);
+        wire::read_response(&mut reader, req.method, self.max_body)
+    }
+}
+
+/// Rewrite `req` for a redirect with `status`.
+///
+/// 303 (and, as browsers do, 301/302 after a `POST`) switch to a bodiless
+/// `GET`; 307/308 repeat the request unchanged. A `Host` override is dropped
+/// since it named the previous server.
+fn redirect_request(req: &mut Request, status: u16) {
+    let to_get = status == 303 || (matches!(status, 301 | 302) && req.method == Method::Post);
+    if to_get && req.method != Method::Head {
+        req.method = Method::Get;
+        req.body.clear();
+        req.headers.retain(|(name, _)| {
+            !name.eq_ignore_ascii_case("Content-Type")
+                && !name.eq_ignore_ascii_case("Content-Length")
+        });
+    }
+    req.headers
+        .retain(|(name, _)| !name.eq_ignore_ascii_case("Host"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_request() {
+        let post = Request::new(Method::Post, "http://a/")
+            .header("Content-Type", "text/plain")
+            .header("Host", "a")
+            .header("X-Keep", "1")
+            .body(b"data".to_vec());
+
+        let mut req = post.clone();
+        redirect_request(&mut req, 303);
+        assert_eq!(req.method, Method::Get);
+        assert!(req.body.is_empty());
+        assert_eq!(req.headers.len(), 1);
+        assert_eq!(req.header_value("x-keep"), Some("1"));
+
+        let mut req = post.clone();
+        redirect_request(&mut req, 307);
+        assert_eq!(req.method, Method::Post);
+        assert_eq!(req.body, b"data");
+        assert_eq!(req.header_value("Host"), None);
+
+        let mut req = Request::new(Method::Head, "http://a/");
+        redirect_request(&mut req, 303);
+        assert_eq!(req.method, Method::Head);
+    }
+
+    #[test]
+    fn test_tls_error() {
+        assert_eq!(Error::from(tls::Error::TimedOut), Error::TimedOut);
+        assert_eq!(
+            Error::from(tls::Error::Io(SyscallError::ConnectionReset)),
+            Error::Io(SyscallError::ConnectionReset)
+        );
+        assert_eq!(
+            Error::from(tls::Error::NameMismatch),
+            Error::Tls(tls::Error::NameMismatch)
+        );
+    }
+}
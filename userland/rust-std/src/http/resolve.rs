@@ -0,0 +1,249 @@
+//! Host name resolution for the HTTP client.
+//!
+//! Lookup order: IPv4 literal, `localhost`, `/etc/hosts`, then an `A` query
+//! over UDP to each `nameserver` in `/etc/resolv.conf` (QEMU's user-mode
+//! resolver, 10.0.2.3, when the file is missing).
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::Error;
+use crate::sys::veridian::{
+    fs,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    path::Path,
+    time::{sleep_ms, Duration, Instant, SystemTime},
+    SyscallError,
+};
+
+/// Fallback resolver when `/etc/resolv.conf` lists none.
+const DEFAULT_NAMESERVER: Ipv4Addr = Ipv4Addr::new(10, 0, 2, 3);
+/// DNS server port.
+const DNS_PORT: u16 = 53;
+/// How long to wait for each nameserver.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+/// DNS record type and class for an IPv4 address.
+const TYPE_A: u16 = 1;
+const CLASS_IN: u16 = 1;
+
+/// Resolve `host` to an IPv4 address.
+pub fn lookup_host(host: &str) -> Result<Ipv4Addr, Error> {
+    if let Some(addr) = parse_ipv4(host) {
+        return Ok(addr);
+    }
+    if host.eq_ignore_ascii_case("localhost") {
+        return Ok(Ipv4Addr::LOCALHOST);
+    }
+    if let Ok(hosts) = fs::read_file(Path::new("/etc/hosts")) {
+        if let Some(addr) = hosts_lookup(&hosts, host) {
+            return Ok(addr);
+        }
+    }
+
+    let mut servers = fs::read_file(Path::new("/etc/resolv.conf"))
+        .map(|conf| nameservers(&conf))
+        .unwrap_or_default();
+    if servers.is_empty() {
+        servers.push(DEFAULT_NAMESERVER);
+    }
+
+    let id = SystemTime::now().subsec_nanos() as u16;
+    let query = build_query(id, host)?;
+    for server in servers {
+        if let Some(addr) = query_server(server, id, &query) {
+            return Ok(addr);
+        }
+    }
+    Err(Error::HostNotFound)
+}
+
+fn query_server(server: Ipv4Addr, id: u16, query: &[u8]) -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind(&SocketAddr::v4(0, 0, 0, 0, 0)).ok()?;
+    let server = SocketAddr::new(crate::sys::veridian::net::IpAddr::V4(server), DNS_PORT);
+    socket.send_to(query, &server).ok()?;
+
+    let deadline = Instant::now().checked_add(QUERY_TIMEOUT)?;
+    let mut buf = [0u8; 512];
+    while Instant::now() < deadline {
+        match socket.recv_from(&mut buf) {
+            Ok((n, from)) if from == server => {
+                if let Some(addr) = parse_answer(id, &buf[..n]) {
+                    return Some(addr);
+                }
+            }
+            Ok(_) => {}
+            Err(SyscallError::WouldBlock) | Err(SyscallError::Interrupted) => {
+                let _ = sleep_ms(10);
+            }
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// Parse a dotted-quad IPv4 literal.
+pub fn parse_ipv4(s: &str) -> Option<Ipv4Addr> {
+    let mut octets = [0u8; 4];
+    let mut parts = s.split('.');
+    for octet in &mut octets {
+        let part = parts.next()?;
+        if part.is_empty() || part.len() > 3 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *octet = part.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+}
+
+/// Words of each non-comment line of a config file.
+fn config_lines(content: &[u8]) -> impl Iterator<Item = impl Iterator<Item = &str>> {
+    content.split(|&b| b == b'\n').map(|line| {
+        let line = core::str::from_utf8(line).unwrap_or("");
+        line.split('#').next().unwrap_or("").split_whitespace()
+    })
+}
+
+fn hosts_lookup(content: &[u8], host: &str) -> Option<Ipv4Addr> {
+    config_lines(content).find_map(|mut words| {
+        let addr = parse_ipv4(words.next()?)?;
+        words
+            .any(|name| name.eq_ignore_ascii_case(host))
+            .then_some(addr)
+    })
+}
+
+fn nameservers(content: &[u8]) -> Vec<Ipv4Addr> {
+    config_lines(content)
+        .filter_map(|mut words| match words.next() {
+            Some("nameserver") => parse_ipv4(words.next()?),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Build a recursive `A` query for `host`.
+fn build_query(id: u16, host: &str) -> Result<Vec<u8>, Error> {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    if host.is_empty() || host.len() > 253 {
+        return Err(Error::HostNotFound);
+    }
+
+    let mut packet = Vec::with_capacity(18 + host.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    // RD set; one question, no other records.
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in host.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(Error::HostNotFound);
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_A.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+fn be16(packet: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(at)?, *packet.get(at + 1)?]))
+}
+
+/// Offset just past the (possibly compressed) name starting at `at`.
+fn skip_name(packet: &[u8], mut at: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(at)?;
+        match len {
+            0 => return Some(at + 1),
+            l if l & 0xC0 == 0xC0 => return Some(at + 2),
+            l if l & 0xC0 == 0 => at += 1 + l as usize,
+            _ => return None,
+        }
+    }
+}
+
+/// First `A` record in a response to query `id`.
+fn parse_answer(id: u16, packet: &[u8]) -> Option<Ipv4Addr> {
+    let flags = be16(packet, 2)?;
+    // Must be a response (QR) with RCODE 0.
+    if be16(packet, 0)? != id || flags & 0x8000 == 0 || flags & 0x000F != 0 {
+        return None;
+    }
+    let questions = be16(packet, 4)?;
+    let answers = be16(packet, 6)?;
+
+    let mut at = 12;
+    for _ in 0..questions {
+        at = skip_name(packet, at)? + 4;
+    }
+    for _ in 0..answers {
+        at = skip_name(packet, at)?;
+        let rtype = be16(packet, at)?;
+        let class = be16(packet, at + 2)?;
+        let rdlen = be16(packet, at + 8)? as usize;
+        let rdata = packet.get(at + 10..at + 10 + rdlen)?;
+        if rtype == TYPE_A && class == CLASS_IN && rdlen == 4 {
+            return Some(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]));
+        }
+        at += 10 + rdlen;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ipv4() {
+        assert_eq!(parse_ipv4("10.0.2.2"), Some(Ipv4Addr::new(10, 0, 2, 2)));
+        assert_eq!(parse_ipv4("256.0.0.1"), None);
+        assert_eq!(parse_ipv4("1.2.3"), None);
+        assert_eq!(parse_ipv4("1.2.3.4.5"), None);
+        assert_eq!(parse_ipv4("repo.example"), None);
+    }
+
+    #[test]
+    fn test_config_files() {
+        let hosts = b"# comment\n127.0.0.1 localhost\n10.0.2.2  repo mirror # local\n";
+        assert_eq!(
+            hosts_lookup(hosts, "MIRROR"),
+            Some(Ipv4Addr::new(10, 0, 2, 2))
+        );
+        assert_eq!(hosts_lookup(hosts, "comment"), None);
+
+        let resolv = b"search lan\nnameserver 1.1.1.1\nnameserver bogus\nnameserver 9.9.9.9\n";
+        assert_eq!(
+            nameservers(resolv),
+            [Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(9, 9, 9, 9)]
+        );
+    }
+
+    #[test]
+    fn test_query_and_answer() {
+        let query = build_query(0x1234, "pkg.example.").unwrap();
+        assert_eq!(&query[12..], b"\x03pkg\x07example\x00\x00\x01\x00\x01");
+        assert!(build_query(1, "a..b").is_err());
+
+        // Response: the question, a CNAME via a compression pointer, then
+        // the A record.
+        let mut resp = query.clone();
+        resp[2] = 0x81;
+        resp[3] = 0x80;
+        resp[7] = 2;
+        resp.extend_from_slice(b"\xc0\x0c\x00\x05\x00\x01\x00\x00\x00\x3c\x00\x02\xc0\x0c");
+        resp.extend_from_slice(b"\xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\x5d\xb8\xd8\x22");
+        assert_eq!(
+            parse_answer(0x1234, &resp),
+            Some(Ipv4Addr::new(93, 184, 216, 34))
+        );
+        assert_eq!(parse_answer(0x4321, &resp), None);
+        assert_eq!(parse_answer(0x1234, &resp[..resp.len() - 2]), None);
+
+        resp[3] = 0x83; // NXDOMAIN
+        assert_eq!(parse_answer(0x1234, &resp), None);
+    }
+}
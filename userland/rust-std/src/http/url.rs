@@ -0,0 +1,269 @@
+//! `http://` and `https://` URL parsing and redirect resolution.
+
+extern crate alloc;
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+
+use super::Error;
+
+/// Default port for the `http` scheme.
+pub const DEFAULT_PORT: u16 = 80;
+/// Default port for the `https` scheme.
+pub const DEFAULT_TLS_PORT: u16 = 443;
+
+/// URL scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Http,
+    /// HTTP over TLS 1.3.
+    Https,
+}
+
+impl Scheme {
+    /// Scheme name as written in URLs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+        }
+    }
+
+    /// Port used when the URL names none.
+    pub fn default_port(self) -> u16 {
+        match self {
+            Scheme::Http => DEFAULT_PORT,
+            Scheme::Https => DEFAULT_TLS_PORT,
+        }
+    }
+}
+
+/// A parsed `http[s]://host[:port]/path?query` URL.
+///
+/// Userinfo is rejected and fragments are dropped, since neither is ever
+/// sent on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    scheme: Scheme,
+    host: String,
+    port: u16,
+    /// Path plus query, always starting with `/`.
+    target: String,
+}
+
+impl Url {
+    /// Parse an absolute URL.
+    pub fn parse(s: &str) -> Result<Url, Error> {
+        let s = s.trim();
+        let (scheme, rest) = s.split_once("://").ok_or(Error::InvalidUrl)?;
+        let scheme = if scheme.eq_ignore_ascii_case("http") {
+            Scheme::Http
+        } else if scheme.eq_ignore_ascii_case("https") {
+            Scheme::Https
+        } else {
+            return Err(Error::UnsupportedScheme);
+        };
+
+        let rest = rest.split('#').next().unwrap_or("");
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, target) = rest.split_at(split);
+        if authority.is_empty() || authority.contains('@') {
+            return Err(Error::InvalidUrl);
+        }
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse::<u16>().map_err(|_| Error::InvalidUrl)?;
+                (host, port)
+            }
+            None => (authority, scheme.default_port()),
+        };
+        if host.is_empty() || port == 0 || !host.bytes().all(is_host_byte) {
+            return Err(Error::InvalidUrl);
+        }
+
+        Ok(Url {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+            target: normalize_target(target)?,
+        })
+    }
+
+    /// Resolve a `Location` header value against this URL.
+    ///
+    /// Handles absolute URLs, scheme-relative (`//host/path`, which keeps
+    /// this URL's scheme), absolute-path and relative-path references.
+    pub fn join(&self, location: &str) -> Result<Url, Error> {
+        let location = location.trim();
+        if location.contains("://") {
+            return Url::parse(location);
+        }
+        if let Some(rest) = location.strip_prefix("//") {
+            return Url::parse(&format!("{}://{}", self.scheme.as_str(), rest));
+        }
+
+        let target = if location.starts_with('/') {
+            location.to_string()
+        } else if location.starts_with('?') {
+            format!("{}{}", self.path(), location)
+        } else {
+            let path = self.path();
+            let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+            format!("{}{}", dir, location)
+        };
+
+        Ok(Url {
+            scheme: self.scheme,
+            host: self.host.clone(),
+            port: self.port,
+            target: normalize_target(target.split('#').next().unwrap_or(""))?,
+        })
+    }
+
+    /// URL scheme.
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Host name or IPv4 literal.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// TCP port.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Request target: path and query.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Path without the query.
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or("/")
+    }
+
+    /// Value for the `Host` request header.
+    pub fn host_header(&self) -> String {
+        if self.port == self.scheme.default_port() {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl core::fmt::Display for Url {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}://{}{}",
+            self.scheme.as_str(),
+            self.host_header(),
+            self.target
+        )
+    }
+}
+
+fn is_host_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'.'
+}
+
+/// Prefix a `/`, collapse `.` and `..` segments and reject bytes that may
+/// not appear in a request line.
+fn normalize_target(target: &str) -> Result<String, Error> {
+    if target.bytes().any(|b| b <= b' ' || b == 0x7f || b >= 0x80) {
+        return Err(Error::InvalidUrl);
+    }
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+
+    let mut out = String::new();
+    let mut segments: alloc::vec::Vec<&str> = alloc::vec::Vec::new();
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    // A trailing `.` or `..` still names a directory.
+    let trailing_dir = path.ends_with("/.") || path.ends_with("/..");
+    for segment in &segments {
+        out.push('/');
+        out.push_str(segment);
+    }
+    if out.is_empty() || trailing_dir {
+        out.push('/');
+    }
+    if let Some(query) = query {
+        out.push('?');
+        out.push_str(query);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let url = Url::parse("http://Repo.Example:8080/pkgs/hello.vpk?v=1#top").unwrap();
+        assert_eq!(url.host(), "repo.example");
+        assert_eq!(url.port(), 8080);
+        assert_eq!(url.target(), "/pkgs/hello.vpk?v=1");
+        assert_eq!(url.path(), "/pkgs/hello.vpk");
+        assert_eq!(url.host_header(), "repo.example:8080");
+
+        let url = Url::parse("http://10.0.2.2").unwrap();
+        assert_eq!(url.port(), DEFAULT_PORT);
+        assert_eq!(url.target(), "/");
+        assert_eq!(url.to_string(), "http://10.0.2.2/");
+
+        let url = Url::parse("HTTPS://example.com/x").unwrap();
+        assert_eq!(url.scheme(), Scheme::Https);
+        assert_eq!(url.port(), DEFAULT_TLS_PORT);
+        assert_eq!(url.host_header(), "example.com");
+        assert_eq!(url.to_string(), "https://example.com/x");
+        let url = Url::parse("https://example.com:80/").unwrap();
+        assert_eq!(url.host_header(), "example.com:80");
+
+        assert_eq!(
+            Url::parse("ftp://example.com/"),
+            Err(Error::UnsupportedScheme)
+        );
+        assert_eq!(Url::parse("example.com/"), Err(Error::InvalidUrl));
+        assert_eq!(Url::parse("http://user@host/"), Err(Error::InvalidUrl));
+        assert_eq!(Url::parse("http://host:0/"), Err(Error::InvalidUrl));
+        assert_eq!(Url::parse("http://host/a b"), Err(Error::InvalidUrl));
+    }
+
+    #[test]
+    fn test_join() {
+        let base = Url::parse("http://host/a/b/c?x=1").unwrap();
+        assert_eq!(base.join("/d").unwrap().target(), "/d");
+        assert_eq!(base.join("d").unwrap().target(), "/a/b/d");
+        assert_eq!(base.join("../d").unwrap().target(), "/a/d");
+        assert_eq!(base.join("?y=2").unwrap().target(), "/a/b/c?y=2");
+        assert_eq!(base.join("//other:81/e").unwrap().host_header(), "other:81");
+        assert_eq!(
+            base.join("http://third/f").unwrap().to_string(),
+            "http://third/f"
+        );
+        assert_eq!(base.join("ftp://other/"), Err(Error::UnsupportedScheme));
+
+        let secure = Url::parse("https://secure/a").unwrap();
+        assert_eq!(secure.join("//cdn/b").unwrap().to_string(), "https://cdn/b");
+        assert_eq!(secure.join("c").unwrap().scheme(), Scheme::Https);
+    }
+}
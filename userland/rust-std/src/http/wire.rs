@@ -0,0 +1,489 @@
+//! HTTP/1.1 message framing: request serialization and response parsing
+//! (status line, headers, `Content-Length`, chunked and read-to-close
+//! bodies).
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use super::{Error, Method, Request, Response, Transport, Url};
+use crate::sys::veridian::{
+    time::{sleep_ms, Instant},
+    SyscallError,
+};
+
+/// Longest status line, header line or chunk-size line accepted.
+const MAX_LINE: usize = 8192;
+/// Most header fields accepted in one response.
+const MAX_HEADERS: usize = 128;
+/// Size of the receive buffer.
+const READ_CHUNK: usize = 4096;
+/// Poll interval when the socket reports `WouldBlock`.
+const POLL_INTERVAL_MS: u64 = 10;
+
+/// Serialize `req` for `url` into a complete HTTP/1.1 request.
+///
+/// `Host`, `User-Agent`, `Accept`, `Connection` and `Content-Length` are
+/// supplied unless the caller set them; the connection is always closed
+/// after one exchange.
+pub(crate) fn encode_request(req: &Request, url: &Url, user_agent: &str) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(256 + req.body.len());
+    push_all(
+        &mut out,
+        &[req.method.as_str(), " ", url.target(), " HTTP/1.1\r\n"],
+    );
+
+    let host = url.host_header();
+    let defaults = [
+        ("Host", host.as_str()),
+        ("User-Agent", user_agent),
+        ("Accept", "*/*"),
+        ("Connection", "close"),
+    ];
+    for (name, value) in defaults {
+        if req.header_value(name).is_none() {
+            push_all(&mut out, &[name, ": ", value, "\r\n"]);
+        }
+    }
+
+    for (name, value) in &req.headers {
+        if name.is_empty() || !name.bytes().all(is_token_byte) || value.bytes().any(is_ctl) {
+            return Err(Error::InvalidHeader);
+        }
+        push_all(&mut out, &[name, ": ", value, "\r\n"]);
+    }
+
+    let has_body = !req.body.is_empty() || matches!(req.method, Method::Post | Method::Put);
+    if has_body && req.header_value("Content-Length").is_none() {
+        let mut digits = [0u8; 20];
+        push_all(
+            &mut out,
+            &[
+                "Content-Length: ",
+                format_usize(req.body.len(), &mut digits),
+                "\r\n",
+            ],
+        );
+    }
+    out.extend_from_slice(b"\r\n");
+    out.extend_from_slice(&req.body);
+    Ok(out)
+}
+
+fn push_all(out: &mut Vec<u8>, parts: &[&str]) {
+    for part in parts {
+        out.extend_from_slice(part.as_bytes());
+    }
+}
+
+fn format_usize(mut n: usize, buf: &mut [u8; 20]) -> &str {
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    core::str::from_utf8(&buf[i..]).unwrap_or("0")
+}
+
+/// RFC 9110 `tchar`.
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn is_ctl(b: u8) -> bool {
+    (b < b' ' && b != b'\t') || b == 0x7f
+}
+
+/// Buffered reader over a transport that enforces an optional deadline.
+pub(crate) struct Reader<'a, T: Transport + ?Sized> {
+    inner: &'a mut T,
+    buf: Vec<u8>,
+    pos: usize,
+    deadline: Option<Instant>,
+    eof: bool,
+}
+
+impl<'a, T: Transport + ?Sized> Reader<'a, T> {
+    pub(crate) fn new(inner: &'a mut T, deadline: Option<Instant>) -> Self {
+        Reader {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            deadline,
+            eof: false,
+        }
+    }
+
+    fn buffered(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Pull more bytes from the transport. Returns `false` at end of stream.
+    fn fill(&mut self) -> Result<bool, Error> {
+        if self.eof {
+            return Ok(false);
+        }
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        let start = self.buf.len();
+        self.buf.resize(start + READ_CHUNK, 0);
+        loop {
+            check_deadline(self.deadline)?;
+            match self.inner.read(&mut self.buf[start..]) {
+                Ok(0) => {
+                    self.buf.truncate(start);
+                    self.eof = true;
+                    return Ok(false);
+                }
+                Ok(n) => {
+                    self.buf.truncate(start + n);
+                    return Ok(true);
+                }
+                Err(SyscallError::WouldBlock) | Err(SyscallError::Interrupted) => {
+                    let _ = sleep_ms(POLL_INTERVAL_MS);
+                }
+                Err(SyscallError::TimedOut) => {
+                    self.buf.truncate(start);
+                    return Err(Error::TimedOut);
+                }
+                Err(e) => {
+                    self.buf.truncate(start);
+                    return Err(Error::Io(e));
+                }
+            }
+        }
+    }
+
+    /// Read one line, without its `\r\n` (or bare `\n`) terminator.
+    fn read_line(&mut self) -> Result<Vec<u8>, Error> {
+        let mut scanned = 0;
+        loop {
+            if let Some(i) = self.buffered()[scanned..].iter().position(|&b| b == b'\n') {
+                let end = scanned + i;
+                let mut line = self.buffered()[..end].to_vec();
+                self.pos += end + 1;
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return Ok(line);
+            }
+            scanned = self.buffered().len();
+            if scanned > MAX_LINE {
+                return Err(Error::Malformed("line too long"));
+            }
+            if !self.fill()? {
+                return Err(Error::Malformed("connection closed mid-line"));
+            }
+        }
+    }
+
+    /// Append exactly `n` bytes to `out`.
+    fn read_exact(&mut self, mut n: usize, out: &mut Vec<u8>) -> Result<(), Error> {
+        while n > 0 {
+            if self.buffered().is_empty() && !self.fill()? {
+                return Err(Error::Malformed("connection closed mid-body"));
+            }
+            let take = n.min(self.buffered().len());
+            out.extend_from_slice(&self.buffered()[..take]);
+            self.pos += take;
+            n -= take;
+        }
+        Ok(())
+    }
+
+    /// Append everything up to end of stream, failing past `limit` bytes.
+    fn read_to_end(&mut self, out: &mut Vec<u8>, limit: usize) -> Result<(), Error> {
+        loop {
+            let chunk = self.buffered();
+            if out.len() + chunk.len() > limit {
+                return Err(Error::BodyTooLarge);
+            }
+            out.extend_from_slice(chunk);
+            self.pos = self.buf.len();
+            if !self.fill()? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn check_deadline(deadline: Option<Instant>) -> Result<(), Error> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(Error::TimedOut),
+        _ => Ok(()),
+    }
+}
+
+/// Read one complete response. Interim `1xx` responses are skipped.
+pub(crate) fn read_response<T: Transport + ?Sized>(
+    reader: &mut Reader<'_, T>,
+    method: Method,
+    max_body: usize,
+) -> Result<Response, Error> {
+    loop {
+        let (status, reason) = parse_status_line(&reader.read_line()?)?;
+        let headers = read_headers(reader)?;
+        let mut response = Response {
+            status,
+            reason,
+            headers,
+            body: Vec::new(),
+        };
+        if (100..200).contains(&status) {
+            continue;
+        }
+        if method == Method::Head || status == 204 || status == 304 {
+            return Ok(response);
+        }
+
+        if let Some(te) = response.header("Transfer-Encoding") {
+            // Only a final `chunked` coding frames the body; anything else
+            // (gzip alone, say) is delimited by connection close.
+            let last = te.rsplit(',').next().unwrap_or("").trim();
+            if last.eq_ignore_ascii_case("chunked") {
+                read_chunked(reader, &mut response.body, max_body)?;
+                return Ok(response);
+            }
+            reader.read_to_end(&mut response.body, max_body)?;
+            return Ok(response);
+        }
+
+        match content_length(&response)? {
+            Some(len) if len > max_body => return Err(Error::BodyTooLarge),
+            Some(len) => reader.read_exact(len, &mut response.body)?,
+            None => reader.read_to_end(&mut response.body, max_body)?,
+        }
+        return Ok(response);
+    }
+}
+
+fn parse_status_line(line: &[u8]) -> Result<(u16, String), Error> {
+    let line = core::str::from_utf8(line).map_err(|_| Error::Malformed("status line"))?;
+    let mut parts = line.splitn(3, ' ');
+    let version = parts.next().unwrap_or("");
+    if !version.starts_with("HTTP/1.") {
+        return Err(Error::Malformed("status line"));
+    }
+    let code = parts.next().unwrap_or("");
+    if code.len() != 3 {
+        return Err(Error::Malformed("status code"));
+    }
+    let status = code
+        .parse::<u16>()
+        .map_err(|_| Error::Malformed("status code"))?;
+    Ok((status, String::from(parts.next().unwrap_or("").trim())))
+}
+
+fn read_headers<T: Transport + ?Sized>(
+    reader: &mut Reader<'_, T>,
+) -> Result<Vec<(String, String)>, Error> {
+    let mut headers = Vec::new();
+    loop {
+        let line = reader.read_line()?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(Error::Malformed("too many headers"));
+        }
+        let colon = line
+            .iter()
+            .position(|&b| b == b':')
+            .ok_or(Error::Malformed("header line"))?;
+        let (name, value) = (&line[..colon], &line[colon + 1..]);
+        if name.is_empty() || !name.iter().all(|&b| is_token_byte(b)) {
+            return Err(Error::Malformed("header name"));
+        }
+        // Header values are Latin-1 in theory; keep them lossless for ASCII
+        // and replace anything else.
+        let value = String::from_utf8_lossy(value);
+        headers.push((
+            String::from_utf8_lossy(name).into_owned(),
+            String::from(value.trim()),
+        ));
+    }
+}
+
+fn content_length(response: &Response) -> Result<Option<usize>, Error> {
+    let mut length = None;
+    for (name, value) in &response.headers {
+        if !name.eq_ignore_ascii_case("Content-Length") {
+            continue;
+        }
+        let n = value
+            .parse::<usize>()
+            .map_err(|_| Error::Malformed("Content-Length"))?;
+        if length.is_some_and(|prev| prev != n) {
+            return Err(Error::Malformed("conflicting Content-Length"));
+        }
+        length = Some(n);
+    }
+    Ok(length)
+}
+
+fn read_chunked<T: Transport + ?Sized>(
+    reader: &mut Reader<'_, T>,
+    out: &mut Vec<u8>,
+    max_body: usize,
+) -> Result<(), Error> {
+    loop {
+        let line = reader.read_line()?;
+        let size = line.split(|&b| b == b';').next().unwrap_or(&[]);
+        let size = core::str::from_utf8(size)
+            .ok()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .and_then(|s| usize::from_str_radix(s, 16).ok())
+            .ok_or(Error::Malformed("chunk size"))?;
+
+        if size == 0 {
+            // Trailer fields are read and discarded.
+            while !reader.read_line()?.is_empty() {}
+            return Ok(());
+        }
+        if out.len().saturating_add(size) > max_body {
+            return Err(Error::BodyTooLarge);
+        }
+        reader.read_exact(size, out)?;
+        if !reader.read_line()?.is_empty() {
+            return Err(Error::Malformed("chunk terminator"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    /// Serves `data` in `step`-byte reads and records what was written.
+    struct Script {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+        sent: Vec<u8>,
+    }
+
+    impl Script {
+        fn new(data: &[u8], step: usize) -> Self {
+            Script {
+                data: data.to_vec(),
+                pos: 0,
+                step,
+                sent: Vec::new(),
+            }
+        }
+    }
+
+    impl Transport for Script {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, SyscallError> {
+            let n = self.step.min(buf.len()).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+
+        fn write_all(&mut self, data: &[u8]) -> Result<(), SyscallError> {
+            self.sent.extend_from_slice(data);
+            Ok(())
+        }
+    }
+
+    fn parse(raw: &[u8], step: usize, method: Method) -> Result<Response, Error> {
+        let mut script = Script::new(raw, step);
+        let mut reader = Reader::new(&mut script, None);
+        read_response(&mut reader, method, 1 << 20)
+    }
+
+    #[test]
+    fn test_encode_request() {
+        let url = Url::parse("http://repo:8080/hello.vpk?x=1").unwrap();
+        let req = Request::new(Method::Post, "http://repo:8080/hello.vpk?x=1")
+            .header("Content-Type", "text/plain")
+            .header("Accept", "text/plain")
+            .body(b"hi".to_vec());
+        let raw = encode_request(&req, &url, "test/1").unwrap();
+        assert_eq!(
+            core::str::from_utf8(&raw).unwrap(),
+            "POST /hello.vpk?x=1 HTTP/1.1\r\nHost: repo:8080\r\nUser-Agent: test/1\r\nConnection: \
+             close\r\nContent-Type: text/plain\r\nAccept: text/plain\r\nContent-Length: \
+             2\r\n\r\nhi"
+        );
+
+        let bad = Request::new(Method::Get, "http://repo/").header("X", "a\r\nInjected: 1");
+        assert_eq!(encode_request(&bad, &url, "t"), Err(Error::InvalidHeader));
+    }
+
+    #[test]
+    fn test_content_length() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nX-A: b\r\n\r\nhelloEXTRA";
+        for step in [1, 3, 4096] {
+            let resp = parse(raw, step, Method::Get).unwrap();
+            assert_eq!(resp.status, 200);
+            assert_eq!(resp.reason, "OK");
+            assert_eq!(resp.header("x-a"), Some("b"));
+            assert_eq!(resp.body, b"hello");
+        }
+
+        let short = b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nhello";
+        assert!(matches!(
+            parse(short, 7, Method::Get),
+            Err(Error::Malformed(_))
+        ));
+        let conflict = b"HTTP/1.1 200 OK\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\nab";
+        assert!(parse(conflict, 64, Method::Get).is_err());
+    }
+
+    #[test]
+    fn test_chunked() {
+        let raw = b"HTTP/1.1 100 Continue\r\n\r\n\
+                    HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nTrailer: x\r\n\r\n";
+        for step in [1, 2, 4096] {
+            let resp = parse(raw, step, Method::Get).unwrap();
+            assert_eq!(resp.status, 200);
+            assert_eq!(resp.body, b"hello, world");
+        }
+
+        let bad = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+        assert_eq!(
+            parse(bad, 64, Method::Get),
+            Err(Error::Malformed("chunk size"))
+        );
+
+        let mut script = Script::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\n0123456789abcdef\r\n0\r\n\r\n",
+            64,
+        );
+        let mut reader = Reader::new(&mut script, None);
+        assert_eq!(
+            read_response(&mut reader, Method::Get, 8),
+            Err(Error::BodyTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_close_delimited_and_bodyless() {
+        let raw = b"HTTP/1.0 200 OK\r\n\r\nuntil close";
+        assert_eq!(parse(raw, 4, Method::Get).unwrap().body, b"until close");
+
+        let head = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n";
+        assert!(parse(head, 64, Method::Head).unwrap().body.is_empty());
+
+        let moved = b"HTTP/1.1 304 Not Modified\r\n\r\n";
+        assert_eq!(parse(moved, 64, Method::Get).unwrap().status, 304);
+
+        assert!(parse(b"SSH-2.0-x\r\n\r\n", 64, Method::Get).is_err());
+        assert!(parse(&vec![b'a'; MAX_LINE * 2], 4096, Method::Get).is_err());
+        assert_eq!(
+            parse(b"HTTP/1.1 200 OK\r\nBad Header\r\n\r\n", 64, Method::Get),
+            Err(Error::Malformed("header line"))
+        );
+    }
+}
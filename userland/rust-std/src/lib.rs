@@ -28,7 +28,8 @@
 //! - **Time**: clock_gettime, nanosleep
 //! - **I/O**: stdin/stdout/stderr via fd 0/1/2
 //! - **OS**: environment variables, command-line arguments
-//! - **Network**: stub (not yet available in kernel)
+//! - **Network**: TCP/UDP sockets, an HTTP/1.1 client in [`http`], and a TLS
+//!   1.3 client in [`tls`]
 //!
 //! # Usage
 //!
@@ -49,7 +50,9 @@
 #![no_std]
 #![allow(dead_code)]
 
+pub mod http;
 pub mod sys;
+pub mod tls;
 
 /// Re-export the VeridianOS platform module for convenience.
 pub use sys::veridian as platform;
@@ -15,6 +15,13 @@
 //! - All allocations are page-aligned at the mmap level; the user pointer is
 //!   offset by the allocation header size.
 //!
+//! # Heap profiling
+//!
+//! With `MALLOC_PROFILE` set, [`init_heap_profile`] turns on the counters
+//! of `veridian_abi::heap_profile::HeapStats` and registers them with
+//! `SYS_HEAP_PROFILE` for `/proc/<pid>/heap`. Rust allocations carry no
+//! return address, so only the counts are kept, whatever mode is asked for.
+//!
 //! # Stack Overflow Protection
 //!
 //! `install_guard_page()` places a `PROT_NONE` guard page at the bottom
@@ -27,6 +34,7 @@
 //! - `munmap` -> SYS_MEMORY_UNMAP (21)
 //! - `mprotect` -> SYS_MEMORY_PROTECT (22)
 //! - `brk` -> SYS_MEMORY_BRK (23)
+//! - heap statistics -> SYS_HEAP_PROFILE (382)
 
 use core::{
     alloc::{GlobalAlloc, Layout},
@@ -34,9 +42,11 @@ use core::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+use veridian_abi::heap_profile::{self, HeapStats};
+
 use super::{
-    syscall1, syscall2, syscall3, syscall6, syscall_result, SyscallError, SYS_MEMORY_BRK,
-    SYS_MEMORY_MAP, SYS_MEMORY_PROTECT, SYS_MEMORY_UNMAP,
+    syscall1, syscall2, syscall3, syscall6, syscall_result, SyscallError, SYS_HEAP_PROFILE,
+    SYS_MEMORY_BRK, SYS_MEMORY_MAP, SYS_MEMORY_PROTECT, SYS_MEMORY_UNMAP,
 };
 
 // ============================================================================
@@ -182,7 +192,7 @@ const fn align_up(size: usize, align: usize) -> usize {
 
 /// Round `size` up to the next page boundary.
 #[inline]
-const fn page_align(size: usize) -> usize {
+pub(crate) const fn page_align(size: usize) -> usize {
     align_up(size, PAGE_SIZE)
 }
 
@@ -406,6 +416,10 @@ unsafe impl GlobalAlloc for VeridianAllocator {
             return align as *mut u8;
         }
 
+        if HEAP_STATS.enabled() {
+            HEAP_STATS.count_alloc(size);
+        }
+
         // Small allocations: use slab pool.
         if size <= SMALL_ALLOC_MAX && align <= PAGE_SIZE {
             let ptr = unsafe { slab_alloc(size, align) };
@@ -459,6 +473,10 @@ unsafe impl GlobalAlloc for VeridianAllocator {
             return;
         }
 
+        if HEAP_STATS.enabled() {
+            HEAP_STATS.count_free(size);
+        }
+
         // Small allocations: return to slab free list.
         if size <= SMALL_ALLOC_MAX && align <= PAGE_SIZE {
             unsafe {
@@ -549,6 +567,30 @@ pub fn alloc_stats() -> AllocStats {
     }
 }
 
+/// Counters registered with `SYS_HEAP_PROFILE`.
+static HEAP_STATS: HeapStats = HeapStats::new();
+
+/// Start counting allocations if `MALLOC_PROFILE` (`value`) asks for it.
+///
+/// Called once at startup, before the first allocation; allocations made
+/// earlier would be freed without having been counted.
+pub fn init_heap_profile(value: &[u8]) -> Result<(), SyscallError> {
+    if heap_profile::parse_mode(value).is_none() {
+        return Ok(());
+    }
+    HEAP_STATS.enable(heap_profile::MODE_COUNTS);
+    // SAFETY: HEAP_STATS is a static, so the kernel may read it for as long
+    // as the process runs.
+    let ret = unsafe {
+        syscall2(
+            SYS_HEAP_PROFILE,
+            &HEAP_STATS as *const HeapStats as usize,
+            core::mem::size_of::<HeapStats>(),
+        )
+    };
+    syscall_result(ret).map(|_| ())
+}
+
 // ============================================================================
 // Stack overflow protection
 // ============================================================================
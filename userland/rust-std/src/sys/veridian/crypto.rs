@@ -0,0 +1,316 @@
+//! Cryptographic primitives for VeridianOS.
+//!
+//! Thin wrappers over the kernel's crypto syscalls, so user-space protocols
+//! share the kernel's implementations rather than bundling their own:
+//!
+//! - `getrandom`  -> SYS_GETRANDOM (330)
+//! - `hash`       -> SYS_CRYPTO_HASH (363)
+//! - `hmac`       -> SYS_CRYPTO_HMAC (364)
+//! - `aead_seal`  -> SYS_CRYPTO_AEAD_SEAL (365)
+//! - `aead_open`  -> SYS_CRYPTO_AEAD_OPEN (366)
+//! - `x25519`     -> SYS_CRYPTO_X25519 (367)
+//! - `verify`     -> SYS_CRYPTO_VERIFY (368)
+//! - `ed25519_sign` -> SYS_CRYPTO_SIGN (369)
+//!
+//! The argument blocks match `kernel/src/syscall/crypto.rs`.
+
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use super::{
+    syscall2, syscall3, syscall5, syscall_result, SyscallError, SYS_CRYPTO_AEAD_OPEN,
+    SYS_CRYPTO_AEAD_SEAL, SYS_CRYPTO_HASH, SYS_CRYPTO_HMAC, SYS_CRYPTO_SIGN, SYS_CRYPTO_VERIFY,
+    SYS_CRYPTO_X25519, SYS_GETRANDOM,
+};
+
+/// AEAD authentication tag length.
+pub const AEAD_TAG_LEN: usize = 16;
+/// AEAD nonce length.
+pub const AEAD_NONCE_LEN: usize = 12;
+/// The X25519 base point, u = 9.
+pub const X25519_BASEPOINT: [u8; 32] = {
+    let mut p = [0u8; 32];
+    p[0] = 9;
+    p
+};
+
+/// Hash functions offered by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256 = 1,
+    Sha384 = 2,
+    Sha512 = 3,
+}
+
+impl HashAlgorithm {
+    /// Digest length in bytes.
+    pub const fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+}
+
+/// AEAD ciphers offered by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aead {
+    Aes128Gcm = 1,
+    ChaCha20Poly1305 = 2,
+}
+
+impl Aead {
+    /// Key length in bytes.
+    pub const fn key_len(self) -> usize {
+        match self {
+            Aead::Aes128Gcm => 16,
+            Aead::ChaCha20Poly1305 => 32,
+        }
+    }
+}
+
+#[repr(C)]
+struct HmacArgs {
+    key: usize,
+    key_len: usize,
+    data: usize,
+    data_len: usize,
+    out: usize,
+    out_len: usize,
+}
+
+#[repr(C)]
+struct AeadArgs {
+    key: usize,
+    key_len: usize,
+    nonce: usize,
+    nonce_len: usize,
+    aad: usize,
+    aad_len: usize,
+    input: usize,
+    input_len: usize,
+    output: usize,
+    output_len: usize,
+}
+
+#[repr(C)]
+struct VerifyArgs {
+    key: usize,
+    key_len: usize,
+    message: usize,
+    message_len: usize,
+    signature: usize,
+    signature_len: usize,
+}
+
+#[repr(C)]
+struct SignArgs {
+    key: usize,
+    key_len: usize,
+    message: usize,
+    message_len: usize,
+    signature: usize,
+    signature_len: usize,
+    public_key: usize,
+    public_key_len: usize,
+}
+
+/// Fill `buf` from the kernel CSPRNG.
+pub fn getrandom(buf: &mut [u8]) -> Result<(), SyscallError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let rest = &mut buf[filled..];
+        // SAFETY: rest is a valid, writable buffer of rest.len() bytes.
+        let ret = unsafe { syscall3(SYS_GETRANDOM, rest.as_mut_ptr() as usize, rest.len(), 0) };
+        filled += syscall_result(ret)?;
+    }
+    Ok(())
+}
+
+/// Hash `data`.
+pub fn hash(alg: HashAlgorithm, data: &[u8]) -> Result<Vec<u8>, SyscallError> {
+    let mut out = vec![0u8; alg.output_len()];
+    // SAFETY: data and out are valid for the lengths passed.
+    let ret = unsafe {
+        syscall5(
+            SYS_CRYPTO_HASH,
+            alg as usize,
+            data.as_ptr() as usize,
+            data.len(),
+            out.as_mut_ptr() as usize,
+            out.len(),
+        )
+    };
+    syscall_result(ret)?;
+    Ok(out)
+}
+
+/// HMAC of `data` under `key`.
+pub fn hmac(alg: HashAlgorithm, key: &[u8], data: &[u8]) -> Result<Vec<u8>, SyscallError> {
+    let mut out = vec![0u8; alg.output_len()];
+    let args = HmacArgs {
+        key: key.as_ptr() as usize,
+        key_len: key.len(),
+        data: data.as_ptr() as usize,
+        data_len: data.len(),
+        out: out.as_mut_ptr() as usize,
+        out_len: out.len(),
+    };
+    // SAFETY: args and every buffer it points to outlive the call.
+    let ret = unsafe { syscall2(SYS_CRYPTO_HMAC, alg as usize, &args as *const _ as usize) };
+    syscall_result(ret)?;
+    Ok(out)
+}
+
+fn aead(
+    nr: usize,
+    alg: Aead,
+    key: &[u8],
+    nonce: &[u8; AEAD_NONCE_LEN],
+    aad: &[u8],
+    input: &[u8],
+    output: &mut [u8],
+) -> Result<usize, SyscallError> {
+    let args = AeadArgs {
+        key: key.as_ptr() as usize,
+        key_len: key.len(),
+        nonce: nonce.as_ptr() as usize,
+        nonce_len: nonce.len(),
+        aad: aad.as_ptr() as usize,
+        aad_len: aad.len(),
+        input: input.as_ptr() as usize,
+        input_len: input.len(),
+        output: output.as_mut_ptr() as usize,
+        output_len: output.len(),
+    };
+    // SAFETY: args and every buffer it points to outlive the call.
+    let ret = unsafe { syscall2(nr, alg as usize, &args as *const _ as usize) };
+    syscall_result(ret)
+}
+
+/// Encrypt `plaintext`, returning ciphertext followed by the 16-byte tag.
+pub fn aead_seal(
+    alg: Aead,
+    key: &[u8],
+    nonce: &[u8; AEAD_NONCE_LEN],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, SyscallError> {
+    let mut out = vec![0u8; plaintext.len() + AEAD_TAG_LEN];
+    let n = aead(
+        SYS_CRYPTO_AEAD_SEAL,
+        alg,
+        key,
+        nonce,
+        aad,
+        plaintext,
+        &mut out,
+    )?;
+    out.truncate(n);
+    Ok(out)
+}
+
+/// Verify and decrypt ciphertext-plus-tag.
+///
+/// Fails with `PermissionDenied` if authentication fails.
+pub fn aead_open(
+    alg: Aead,
+    key: &[u8],
+    nonce: &[u8; AEAD_NONCE_LEN],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, SyscallError> {
+    if ciphertext.len() < AEAD_TAG_LEN {
+        return Err(SyscallError::InvalidArgument);
+    }
+    let mut out = vec![0u8; ciphertext.len() - AEAD_TAG_LEN];
+    let n = aead(
+        SYS_CRYPTO_AEAD_OPEN,
+        alg,
+        key,
+        nonce,
+        aad,
+        ciphertext,
+        &mut out,
+    )?;
+    out.truncate(n);
+    Ok(out)
+}
+
+/// X25519: `scalar * point`. Pass [`X25519_BASEPOINT`] to derive a public
+/// key. Fails with `InvalidArgument` for a small-order `point`.
+pub fn x25519(scalar: &[u8; 32], point: &[u8; 32]) -> Result<[u8; 32], SyscallError> {
+    let mut out = [0u8; 32];
+    // SAFETY: all three buffers are 32 bytes as the kernel expects.
+    let ret = unsafe {
+        syscall3(
+            SYS_CRYPTO_X25519,
+            scalar.as_ptr() as usize,
+            point.as_ptr() as usize,
+            out.as_mut_ptr() as usize,
+        )
+    };
+    syscall_result(ret)?;
+    Ok(out)
+}
+
+/// Verify `signature` over `message`.
+///
+/// `scheme` is a TLS 1.3 `SignatureScheme` code point. `public_key` is a
+/// DER `RSAPublicKey`, an uncompressed SEC1 point or a raw Ed25519 key, as
+/// found in an X.509 subjectPublicKey.
+pub fn verify(
+    scheme: u16,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, SyscallError> {
+    let args = VerifyArgs {
+        key: public_key.as_ptr() as usize,
+        key_len: public_key.len(),
+        message: message.as_ptr() as usize,
+        message_len: message.len(),
+        signature: signature.as_ptr() as usize,
+        signature_len: signature.len(),
+    };
+    // SAFETY: args and every buffer it points to outlive the call.
+    let ret = unsafe {
+        syscall2(
+            SYS_CRYPTO_VERIFY,
+            scheme as usize,
+            &args as *const _ as usize,
+        )
+    };
+    Ok(syscall_result(ret)? == 1)
+}
+
+/// Ed25519 `SignatureScheme` code point.
+pub const ED25519: u16 = 0x0807;
+
+/// Sign `message` with the Ed25519 key whose RFC 8032 seed is `seed`.
+/// Returns the 64-byte signature and the 32-byte public key.
+pub fn ed25519_sign(seed: &[u8; 32], message: &[u8]) -> Result<([u8; 64], [u8; 32]), SyscallError> {
+    let mut signature = [0u8; 64];
+    let mut public_key = [0u8; 32];
+    let args = SignArgs {
+        key: seed.as_ptr() as usize,
+        key_len: seed.len(),
+        message: message.as_ptr() as usize,
+        message_len: message.len(),
+        signature: signature.as_mut_ptr() as usize,
+        signature_len: signature.len(),
+        public_key: public_key.as_mut_ptr() as usize,
+        public_key_len: public_key.len(),
+    };
+    // SAFETY: args and every buffer it points to outlive the call.
+    let ret = unsafe {
+        syscall2(
+            SYS_CRYPTO_SIGN,
+            ED25519 as usize,
+            &args as *const _ as usize,
+        )
+    };
+    syscall_result(ret)?;
+    Ok((signature, public_key))
+}
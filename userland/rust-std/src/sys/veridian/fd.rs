@@ -4,6 +4,11 @@
 //! automatically closes the underlying fd when all references are dropped.
 //! This is the foundation type used by `File`, `TcpStream`, `UdpSocket`,
 //! and other I/O types.
+//!
+//! The [`AsRawFd`], [`IntoRawFd`] and [`FromRawFd`] traits mirror their
+//! `std::os::fd` namesakes, so code can accept any descriptor-backed type
+//! and hand ownership between them without an intermediate raw `usize`
+//! that nothing closes.
 
 extern crate alloc;
 
@@ -11,6 +16,31 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 
 use super::{fs, SyscallError};
 
+// ============================================================================
+// Conversion traits
+// ============================================================================
+
+/// A type that wraps an open file descriptor.
+pub trait AsRawFd {
+    /// The descriptor, still owned by `self`.
+    fn as_raw_fd(&self) -> usize;
+}
+
+/// A type whose descriptor can be released to the caller.
+pub trait IntoRawFd {
+    /// Give up ownership of the descriptor without closing it.
+    fn into_raw_fd(self) -> usize;
+}
+
+/// A type that can take ownership of a raw descriptor.
+pub trait FromRawFd {
+    /// Wrap `fd`, which will be closed when the result is dropped.
+    ///
+    /// # Safety
+    /// The caller must own `fd` and must not close or wrap it again.
+    unsafe fn from_raw_fd(fd: usize) -> Self;
+}
+
 // ============================================================================
 // SharedFd -- reference-counted file descriptor
 // ============================================================================
@@ -134,6 +164,34 @@ impl Drop for SharedFd {
     }
 }
 
+impl AsRawFd for SharedFd {
+    fn as_raw_fd(&self) -> usize {
+        self.raw()
+    }
+}
+
+impl IntoRawFd for SharedFd {
+    /// Release the descriptor; other clones still referencing it keep it
+    /// open, so this is only sound on the last clone.
+    fn into_raw_fd(self) -> usize {
+        self.into_raw()
+    }
+}
+
+impl FromRawFd for SharedFd {
+    unsafe fn from_raw_fd(fd: usize) -> Self {
+        // SAFETY: forwarded from the caller.
+        unsafe { SharedFd::from_raw(fd) }
+    }
+}
+
+impl From<OwnedFd> for SharedFd {
+    fn from(fd: OwnedFd) -> Self {
+        // SAFETY: `into_raw` hands over sole ownership.
+        unsafe { SharedFd::from_raw(fd.into_raw()) }
+    }
+}
+
 impl core::fmt::Debug for SharedFd {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("SharedFd").field("fd", &self.raw()).finish()
@@ -199,6 +257,24 @@ impl Drop for OwnedFd {
     }
 }
 
+impl AsRawFd for OwnedFd {
+    fn as_raw_fd(&self) -> usize {
+        self.fd
+    }
+}
+
+impl IntoRawFd for OwnedFd {
+    fn into_raw_fd(self) -> usize {
+        self.into_raw()
+    }
+}
+
+impl FromRawFd for OwnedFd {
+    unsafe fn from_raw_fd(fd: usize) -> Self {
+        OwnedFd { fd }
+    }
+}
+
 impl core::fmt::Debug for OwnedFd {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("OwnedFd").field("fd", &self.fd).finish()
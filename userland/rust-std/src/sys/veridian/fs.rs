@@ -28,18 +28,21 @@
 //! - `readdir` -> SYS_DIR_READDIR (63)
 //! - `closedir`-> SYS_DIR_CLOSEDIR (64)
 //! - `fsync`   -> SYS_FS_FSYNC (73)
+//! - `sendfile`-> SYS_SENDFILE (370)
+//! - `splice`  -> SYS_SPLICE (371)
 
 extern crate alloc;
 use alloc::vec::Vec;
 
 use super::{
-    fd::SharedFd,
+    fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, SharedFd},
     path::{OsStr, OsString, Path, PathBuf},
-    syscall1, syscall2, syscall3, syscall_result, SyscallError, SYS_DIR_CLOSEDIR, SYS_DIR_MKDIR,
-    SYS_DIR_OPENDIR, SYS_DIR_READDIR, SYS_DIR_RMDIR, SYS_FILE_CLOSE, SYS_FILE_DUP, SYS_FILE_DUP2,
-    SYS_FILE_LINK, SYS_FILE_OPEN, SYS_FILE_PIPE, SYS_FILE_READ, SYS_FILE_READLINK, SYS_FILE_RENAME,
-    SYS_FILE_SEEK, SYS_FILE_STAT, SYS_FILE_STAT_PATH, SYS_FILE_SYMLINK, SYS_FILE_TRUNCATE,
-    SYS_FILE_UNLINK, SYS_FILE_WRITE, SYS_FS_FSYNC,
+    syscall1, syscall2, syscall3, syscall4, syscall5, syscall_result, SyscallError,
+    SYS_DIR_CLOSEDIR, SYS_DIR_MKDIR, SYS_DIR_OPENDIR, SYS_DIR_READDIR, SYS_DIR_RMDIR,
+    SYS_FILE_CLOSE, SYS_FILE_DUP, SYS_FILE_DUP2, SYS_FILE_LINK, SYS_FILE_OPEN, SYS_FILE_PIPE,
+    SYS_FILE_READ, SYS_FILE_READLINK, SYS_FILE_RENAME, SYS_FILE_SEEK, SYS_FILE_STAT,
+    SYS_FILE_STAT_PATH, SYS_FILE_SYMLINK, SYS_FILE_TRUNCATE, SYS_FILE_UNLINK, SYS_FILE_WRITE,
+    SYS_FS_FSYNC, SYS_SENDFILE, SYS_SPLICE,
 };
 
 // ============================================================================
@@ -313,6 +316,43 @@ pub fn fsync(fd: usize) -> Result<usize, SyscallError> {
     syscall_result(ret)
 }
 
+/// Copy up to `count` bytes from the file `in_fd` to `out_fd` (a socket,
+/// pipe or file) inside the kernel.
+///
+/// `offset` is null to use and advance the file position of `in_fd`, or
+/// points to an `i64` offset that is read from and advanced instead.
+pub fn sendfile(
+    out_fd: usize,
+    in_fd: usize,
+    offset: *mut i64,
+    count: usize,
+) -> Result<usize, SyscallError> {
+    let ret = unsafe { syscall4(SYS_SENDFILE, out_fd, in_fd, offset as usize, count) };
+    syscall_result(ret)
+}
+
+/// Move up to `len` bytes between a pipe and another descriptor inside the
+/// kernel. Offsets work as for [`sendfile`] and must be null for the pipe.
+pub fn splice(
+    fd_in: usize,
+    off_in: *mut i64,
+    fd_out: usize,
+    off_out: *mut i64,
+    len: usize,
+) -> Result<usize, SyscallError> {
+    let ret = unsafe {
+        syscall5(
+            SYS_SPLICE,
+            fd_in,
+            off_in as usize,
+            fd_out,
+            off_out as usize,
+            len,
+        )
+    };
+    syscall_result(ret)
+}
+
 // ============================================================================
 // Directory Operations
 // ============================================================================
@@ -811,6 +851,33 @@ impl File {
     }
 }
 
+impl AsRawFd for File {
+    fn as_raw_fd(&self) -> usize {
+        self.fd.raw()
+    }
+}
+
+impl IntoRawFd for File {
+    fn into_raw_fd(self) -> usize {
+        self.fd.into_raw()
+    }
+}
+
+impl FromRawFd for File {
+    unsafe fn from_raw_fd(fd: usize) -> Self {
+        File {
+            // SAFETY: forwarded from the caller.
+            fd: unsafe { SharedFd::from_raw(fd) },
+        }
+    }
+}
+
+impl From<OwnedFd> for File {
+    fn from(fd: OwnedFd) -> Self {
+        File { fd: fd.into() }
+    }
+}
+
 impl core::fmt::Debug for File {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("File").field("fd", &self.fd).finish()
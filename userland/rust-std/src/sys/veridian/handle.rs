@@ -0,0 +1,358 @@
+//! Typed handles for kernel resources.
+//!
+//! Each type here owns one kernel resource and releases it in `Drop`, so an
+//! early return or `?` no longer leaks it:
+//!
+//! | Type             | Resource                      | Released by                  |
+//! |------------------|-------------------------------|------------------------------|
+//! | [`OwnedFd`]      | file descriptor               | `SYS_FILE_CLOSE` (51)        |
+//! | [`MappedRegion`] | `mmap` mapping                | `SYS_MEMORY_UNMAP` (21)      |
+//! | [`IpcEndpoint`]  | IPC endpoint capability       | `SYS_CAPABILITY_REVOKE` (31) |
+//! | [`SharedMem`]    | POSIX shared memory reference | `SYS_SHM_CLOSE` (213)        |
+//!
+//! All of them offer `into_raw` to hand the resource to code that manages
+//! it by hand, and an unsafe `from_raw` to take one back. Descriptors and
+//! shared memory objects can be duplicated with `try_clone`; the kernel
+//! has no way to duplicate a mapping or an endpoint capability, so share
+//! those through an `Arc` instead.
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+pub use super::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+use super::{
+    alloc::{
+        mmap, mprotect, munmap, page_align, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE,
+    },
+    syscall1, syscall2, syscall3, syscall_result, SyscallError, SYS_CAPABILITY_REVOKE,
+    SYS_IPC_BIND_ENDPOINT, SYS_IPC_CREATE_ENDPOINT, SYS_IPC_LOOKUP_ENDPOINT, SYS_SHM_CLOSE,
+    SYS_SHM_OPEN, SYS_SHM_TRUNCATE, SYS_SHM_UNLINK,
+};
+
+/// A name as a null-terminated byte string for the kernel.
+fn c_name(name: &str) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(name.len() + 1);
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(0);
+    buf
+}
+
+// ============================================================================
+// MappedRegion
+// ============================================================================
+
+/// A memory mapping, unmapped on drop.
+pub struct MappedRegion {
+    addr: usize,
+    len: usize,
+    prot: usize,
+}
+
+impl MappedRegion {
+    /// Map `len` bytes (rounded up to whole pages) of zeroed private memory.
+    pub fn anonymous(len: usize, prot: usize) -> Result<Self, SyscallError> {
+        let len = page_align(len);
+        let addr = mmap(0, len, prot, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0)?;
+        Ok(MappedRegion { addr, len, prot })
+    }
+
+    /// Map `len` bytes of the file behind `fd`, starting at the page-aligned
+    /// `offset`. The mapping stays valid after `fd` is closed.
+    ///
+    /// # Safety
+    /// Writes to the file through another handle can change the bytes
+    /// behind [`MappedRegion::as_slice`]; the caller must ensure nothing
+    /// modifies the file while the mapping is in use.
+    pub unsafe fn map_fd(
+        fd: &impl AsRawFd,
+        offset: usize,
+        len: usize,
+        prot: usize,
+        flags: usize,
+    ) -> Result<Self, SyscallError> {
+        let len = page_align(len);
+        let addr = mmap(0, len, prot, flags, fd.as_raw_fd() as isize, offset)?;
+        Ok(MappedRegion { addr, len, prot })
+    }
+
+    /// Take ownership of an existing mapping.
+    ///
+    /// # Safety
+    /// `addr..addr + len` must be a mapping with protection `prot` that the
+    /// caller owns and no other handle refers to.
+    pub unsafe fn from_raw(addr: usize, len: usize, prot: usize) -> Self {
+        MappedRegion { addr, len, prot }
+    }
+
+    /// Give up ownership; returns `(addr, len)` and leaves the memory mapped.
+    pub fn into_raw(self) -> (usize, usize) {
+        let raw = (self.addr, self.len);
+        core::mem::forget(self);
+        raw
+    }
+
+    /// Start address of the mapping.
+    #[inline]
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Length in bytes (a whole number of pages).
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the mapping is empty (never true for a successful `mmap`).
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Current protection flags.
+    #[inline]
+    pub fn prot(&self) -> usize {
+        self.prot
+    }
+
+    /// Change the protection of the whole mapping.
+    pub fn protect(&mut self, prot: usize) -> Result<(), SyscallError> {
+        mprotect(self.addr, self.len, prot)?;
+        self.prot = prot;
+        Ok(())
+    }
+
+    /// The mapped bytes.
+    ///
+    /// # Panics
+    /// If the mapping is not readable.
+    pub fn as_slice(&self) -> &[u8] {
+        assert!(self.prot & PROT_READ != 0, "mapping is not readable");
+        // SAFETY: `addr..addr + len` is mapped readable for as long as
+        // `self` lives.
+        unsafe { core::slice::from_raw_parts(self.addr as *const u8, self.len) }
+    }
+
+    /// The mapped bytes, writable.
+    ///
+    /// # Panics
+    /// If the mapping is not readable and writable.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        assert!(
+            self.prot & (PROT_READ | PROT_WRITE) == PROT_READ | PROT_WRITE,
+            "mapping is not writable"
+        );
+        // SAFETY: as for `as_slice`, and `&mut self` makes the borrow
+        // exclusive.
+        unsafe { core::slice::from_raw_parts_mut(self.addr as *mut u8, self.len) }
+    }
+}
+
+impl Drop for MappedRegion {
+    fn drop(&mut self) {
+        let _ = munmap(self.addr, self.len);
+    }
+}
+
+impl core::fmt::Debug for MappedRegion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MappedRegion")
+            .field("addr", &format_args!("{:#x}", self.addr))
+            .field("len", &self.len)
+            .field("prot", &self.prot)
+            .finish()
+    }
+}
+
+// SAFETY: A mapping is process-wide memory; access to its contents goes
+// through `&self`/`&mut self` like any other buffer.
+unsafe impl Send for MappedRegion {}
+unsafe impl Sync for MappedRegion {}
+
+// ============================================================================
+// IpcEndpoint
+// ============================================================================
+
+/// A capability for an IPC endpoint, released from this process's
+/// capability space on drop.
+///
+/// Releasing only removes this process's token: copies the kernel minted
+/// for other processes (for example by [`IpcEndpoint::lookup`]) stay valid.
+pub struct IpcEndpoint {
+    token: usize,
+}
+
+impl IpcEndpoint {
+    /// Create a new endpoint owned by this process.
+    pub fn create() -> Result<Self, SyscallError> {
+        // SAFETY: SYS_IPC_CREATE_ENDPOINT takes no pointers.
+        let ret = unsafe { syscall1(SYS_IPC_CREATE_ENDPOINT, 0) };
+        Ok(IpcEndpoint {
+            token: syscall_result(ret)?,
+        })
+    }
+
+    /// Look up the endpoint bound to `name` in this process's namespace,
+    /// returning a send-only capability for it.
+    pub fn lookup(name: &str) -> Result<Self, SyscallError> {
+        let name = c_name(name);
+        // SAFETY: `name` is a valid null-terminated string.
+        let ret = unsafe { syscall1(SYS_IPC_LOOKUP_ENDPOINT, name.as_ptr() as usize) };
+        Ok(IpcEndpoint {
+            token: syscall_result(ret)?,
+        })
+    }
+
+    /// Publish this endpoint under `name` (requires bind rights).
+    pub fn bind(&self, name: &str) -> Result<(), SyscallError> {
+        let name = c_name(name);
+        // SAFETY: `name` is a valid null-terminated string.
+        let ret = unsafe { syscall2(SYS_IPC_BIND_ENDPOINT, self.token, name.as_ptr() as usize) };
+        syscall_result(ret).map(|_| ())
+    }
+
+    /// The capability token, for the raw IPC syscalls.
+    #[inline]
+    pub fn raw(&self) -> usize {
+        self.token
+    }
+
+    /// Take ownership of a capability token.
+    ///
+    /// # Safety
+    /// The caller must own `token` and must not release or wrap it again.
+    pub unsafe fn from_raw(token: usize) -> Self {
+        IpcEndpoint { token }
+    }
+
+    /// Give up ownership without releasing the capability.
+    pub fn into_raw(self) -> usize {
+        let token = self.token;
+        core::mem::forget(self);
+        token
+    }
+}
+
+impl Drop for IpcEndpoint {
+    fn drop(&mut self) {
+        // SAFETY: SYS_CAPABILITY_REVOKE takes no pointers.
+        let _ = unsafe { syscall1(SYS_CAPABILITY_REVOKE, self.token) };
+    }
+}
+
+impl core::fmt::Debug for IpcEndpoint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IpcEndpoint")
+            .field("token", &format_args!("{:#x}", self.token))
+            .finish()
+    }
+}
+
+// ============================================================================
+// SharedMem
+// ============================================================================
+
+/// `SharedMem::open_with` flag: create the object if it does not exist.
+pub const SHM_CREATE: usize = 1;
+/// `SharedMem::open_with` flag: fail if the object already exists.
+pub const SHM_EXCL: usize = 2;
+/// `SharedMem::open_with` flag: open for reading only.
+pub const SHM_RDONLY: usize = 4;
+
+/// A reference to a named POSIX shared memory object, closed on drop.
+///
+/// The object itself lives until it is unlinked and every reference to it
+/// has been closed.
+pub struct SharedMem {
+    name: String,
+    id: usize,
+}
+
+impl SharedMem {
+    /// Open the existing object `name`.
+    pub fn open(name: &str) -> Result<Self, SyscallError> {
+        Self::open_with(name, 0)
+    }
+
+    /// Create `name`, or open it if it exists.
+    pub fn create(name: &str) -> Result<Self, SyscallError> {
+        Self::open_with(name, SHM_CREATE)
+    }
+
+    /// Open `name` with `SHM_*` flags.
+    pub fn open_with(name: &str, flags: usize) -> Result<Self, SyscallError> {
+        let c = c_name(name);
+        // SAFETY: `c` is a valid null-terminated string.
+        let ret = unsafe { syscall3(SYS_SHM_OPEN, c.as_ptr() as usize, flags, 0) };
+        Ok(SharedMem {
+            name: String::from(name),
+            id: syscall_result(ret)?,
+        })
+    }
+
+    /// The object's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The kernel's id for the object.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Set the object's size in bytes.
+    pub fn truncate(&self, size: usize) -> Result<(), SyscallError> {
+        let c = c_name(&self.name);
+        // SAFETY: `c` is a valid null-terminated string.
+        let ret = unsafe { syscall3(SYS_SHM_TRUNCATE, c.as_ptr() as usize, 0, size) };
+        syscall_result(ret).map(|_| ())
+    }
+
+    /// Remove the name; the memory is freed once every reference is closed.
+    pub fn unlink(&self) -> Result<(), SyscallError> {
+        let c = c_name(&self.name);
+        // SAFETY: `c` is a valid null-terminated string.
+        let ret = unsafe { syscall2(SYS_SHM_UNLINK, c.as_ptr() as usize, 0) };
+        syscall_result(ret).map(|_| ())
+    }
+
+    /// Take another reference to the same object.
+    pub fn try_clone(&self) -> Result<Self, SyscallError> {
+        Self::open(&self.name)
+    }
+
+    /// Take ownership of a reference from `SYS_SHM_OPEN`.
+    ///
+    /// # Safety
+    /// The caller must own a reference to `name` and must not close or wrap
+    /// it again.
+    pub unsafe fn from_raw(name: String, id: usize) -> Self {
+        SharedMem { name, id }
+    }
+
+    /// Give up ownership without closing the reference.
+    pub fn into_raw(self) -> (String, usize) {
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so the name is moved out once.
+        (unsafe { core::ptr::read(&this.name) }, this.id)
+    }
+}
+
+impl Drop for SharedMem {
+    fn drop(&mut self) {
+        let c = c_name(&self.name);
+        // SAFETY: `c` is a valid null-terminated string.
+        let _ = unsafe { syscall2(SYS_SHM_CLOSE, c.as_ptr() as usize, 0) };
+    }
+}
+
+impl core::fmt::Debug for SharedMem {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SharedMem")
+            .field("name", &self.name)
+            .field("id", &self.id)
+            .finish()
+    }
+}
@@ -248,31 +248,9 @@ impl AnonPipe {
         self.write_fd.raw()
     }
 
-    /// Take ownership of the read end (the pipe keeps the write end).
-    ///
-    /// After this call, the read end will NOT be closed when the `AnonPipe`
-    /// is dropped.  The caller owns it.
-    pub fn take_read_fd(&mut self) -> OwnedFd {
-        let fd = self.read_fd.raw();
-        let taken = unsafe { OwnedFd::from_raw(fd) };
-        // Replace the original with a dummy that won't close anything useful.
-        // We dup the write fd as a placeholder -- it'll be closed on drop,
-        // but that's harmless since the original write fd is still held.
-        // Actually, the cleanest approach: just forget the old one.
-        let old_fd =
-            core::mem::replace(&mut self.read_fd, unsafe { OwnedFd::from_raw(usize::MAX) });
-        core::mem::forget(old_fd);
-        taken
-    }
-
-    /// Take ownership of the write end.
-    pub fn take_write_fd(&mut self) -> OwnedFd {
-        let fd = self.write_fd.raw();
-        let taken = unsafe { OwnedFd::from_raw(fd) };
-        let old_fd =
-            core::mem::replace(&mut self.write_fd, unsafe { OwnedFd::from_raw(usize::MAX) });
-        core::mem::forget(old_fd);
-        taken
+    /// Split into the `(read, write)` ends, each closed when dropped.
+    pub fn into_fds(self) -> (OwnedFd, OwnedFd) {
+        (self.read_fd, self.write_fd)
     }
 }
 
@@ -27,10 +27,12 @@
 extern crate alloc;
 
 use super::{
-    fd::SharedFd, syscall1, syscall2, syscall3, syscall5, syscall_result, SyscallError,
-    SYS_NET_GETPEERNAME, SYS_NET_GETSOCKNAME, SYS_NET_GETSOCKOPT, SYS_NET_RECVFROM, SYS_NET_SENDTO,
-    SYS_NET_SETSOCKOPT, SYS_SOCKET_ACCEPT, SYS_SOCKET_BIND, SYS_SOCKET_CLOSE, SYS_SOCKET_CONNECT,
-    SYS_SOCKET_CREATE, SYS_SOCKET_LISTEN, SYS_SOCKET_RECV, SYS_SOCKET_SEND,
+    fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, SharedFd},
+    syscall1, syscall2, syscall3, syscall4, syscall5, syscall_result,
+    time::{Duration, Timeval},
+    SyscallError, SYS_NET_GETPEERNAME, SYS_NET_GETSOCKNAME, SYS_NET_GETSOCKOPT, SYS_NET_RECVFROM,
+    SYS_NET_SENDTO, SYS_NET_SETSOCKOPT, SYS_SOCKET_ACCEPT, SYS_SOCKET_BIND, SYS_SOCKET_CLOSE,
+    SYS_SOCKET_CONNECT, SYS_SOCKET_CREATE, SYS_SOCKET_LISTEN, SYS_SOCKET_RECV, SYS_SOCKET_SEND,
 };
 
 // ============================================================================
@@ -71,6 +73,10 @@ pub const SO_RCVBUF: usize = 8;
 pub const SO_KEEPALIVE: usize = 9;
 /// Socket option: error.
 pub const SO_ERROR: usize = 4;
+/// Socket option: receive timeout (`Timeval`).
+pub const SO_RCVTIMEO: usize = 20;
+/// Socket option: send timeout (`Timeval`).
+pub const SO_SNDTIMEO: usize = 21;
 
 /// TCP option: disable Nagle.
 pub const TCP_NODELAY: usize = 1;
@@ -100,7 +106,7 @@ pub fn listen(fd: usize, backlog: usize) -> Result<usize, SyscallError> {
 
 /// Accept a connection on a socket.
 pub fn accept(fd: usize) -> Result<usize, SyscallError> {
-    let ret = unsafe { syscall1(SYS_SOCKET_ACCEPT, fd) };
+    let ret = unsafe { syscall4(SYS_SOCKET_ACCEPT, fd, 0, 0, 0) };
     syscall_result(ret)
 }
 
@@ -111,14 +117,14 @@ pub fn connect(fd: usize, addr: *const u8, addrlen: usize) -> Result<usize, Sysc
 }
 
 /// Send data on a connected socket.
-pub fn send(fd: usize, buf: *const u8, len: usize, _flags: usize) -> Result<usize, SyscallError> {
-    let ret = unsafe { syscall3(SYS_SOCKET_SEND, fd, buf as usize, len) };
+pub fn send(fd: usize, buf: *const u8, len: usize, flags: usize) -> Result<usize, SyscallError> {
+    let ret = unsafe { syscall4(SYS_SOCKET_SEND, fd, buf as usize, len, flags) };
     syscall_result(ret)
 }
 
 /// Receive data from a connected socket.
-pub fn recv(fd: usize, buf: *mut u8, len: usize, _flags: usize) -> Result<usize, SyscallError> {
-    let ret = unsafe { syscall3(SYS_SOCKET_RECV, fd, buf as usize, len) };
+pub fn recv(fd: usize, buf: *mut u8, len: usize, flags: usize) -> Result<usize, SyscallError> {
+    let ret = unsafe { syscall4(SYS_SOCKET_RECV, fd, buf as usize, len, flags) };
     syscall_result(ret)
 }
 
@@ -151,7 +157,7 @@ pub fn sendto(
 
 /// Receive data and sender address (UDP).
 pub fn recvfrom(fd: usize, buf: *mut u8, len: usize, addr: *mut u8) -> Result<usize, SyscallError> {
-    let ret = unsafe { super::syscall4(SYS_NET_RECVFROM, fd, buf as usize, len, addr as usize) };
+    let ret = unsafe { syscall4(SYS_NET_RECVFROM, fd, buf as usize, len, addr as usize) };
     syscall_result(ret)
 }
 
@@ -188,14 +194,25 @@ pub fn setsockopt(
     syscall_result(ret)
 }
 
-/// Get a socket option.
+/// Get a socket option. `*optlen` gives the room at `optval` and receives
+/// the size written.
 pub fn getsockopt(
     fd: usize,
     level: usize,
     optname: usize,
     optval: *mut u8,
+    optlen: *mut u32,
 ) -> Result<usize, SyscallError> {
-    let ret = unsafe { super::syscall4(SYS_NET_GETSOCKOPT, fd, level, optname, optval as usize) };
+    let ret = unsafe {
+        syscall5(
+            SYS_NET_GETSOCKOPT,
+            fd,
+            level,
+            optname,
+            optval as usize,
+            optlen as usize,
+        )
+    };
     syscall_result(ret)
 }
 
@@ -408,13 +425,10 @@ impl TcpStream {
     }
 
     /// Shut down part or all of the connection.
-    pub fn shutdown(&self, how: usize) -> Result<(), SyscallError> {
-        // VeridianOS uses SYS_SOCKET_CLOSE for full shutdown.
-        // For partial shutdown, we would need a dedicated syscall.
-        // For now, treat SHUT_RDWR as close.
-        if how == SHUT_RDWR {
-            socket_close(self.fd.raw())?;
-        }
+    pub fn shutdown(&self, _how: usize) -> Result<(), SyscallError> {
+        // There is no shutdown syscall yet, and the descriptor stays owned
+        // by `self.fd` (closing it here would close it twice), so this is
+        // a no-op.
         Ok(())
     }
 
@@ -455,6 +469,16 @@ impl TcpStream {
         Ok(())
     }
 
+    /// Set the receive timeout; `None` blocks indefinitely.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), SyscallError> {
+        set_timeout(self.fd.raw(), SO_RCVTIMEO, timeout)
+    }
+
+    /// Set the send timeout; `None` blocks indefinitely.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<(), SyscallError> {
+        set_timeout(self.fd.raw(), SO_SNDTIMEO, timeout)
+    }
+
     /// Set non-blocking mode.
     ///
     /// Currently a no-op placeholder -- requires fcntl or ioctl support.
@@ -476,12 +500,59 @@ impl TcpStream {
     }
 }
 
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> usize {
+        self.fd.raw()
+    }
+}
+
+impl IntoRawFd for TcpStream {
+    fn into_raw_fd(self) -> usize {
+        self.fd.into_raw()
+    }
+}
+
+impl FromRawFd for TcpStream {
+    unsafe fn from_raw_fd(fd: usize) -> Self {
+        TcpStream {
+            // SAFETY: forwarded from the caller.
+            fd: unsafe { SharedFd::from_raw(fd) },
+        }
+    }
+}
+
+impl From<OwnedFd> for TcpStream {
+    fn from(fd: OwnedFd) -> Self {
+        TcpStream { fd: fd.into() }
+    }
+}
+
 impl core::fmt::Debug for TcpStream {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("TcpStream").field("fd", &self.fd).finish()
     }
 }
 
+/// Apply `SO_RCVTIMEO` / `SO_SNDTIMEO`. A zero `Timeval` means no timeout,
+/// so a zero `Duration` is rounded up to one microsecond.
+fn set_timeout(fd: usize, optname: usize, timeout: Option<Duration>) -> Result<(), SyscallError> {
+    let tv = match timeout {
+        Some(d) => Timeval {
+            tv_sec: d.as_secs() as i64,
+            tv_usec: (d.subsec_micros() as i64).max(if d.as_secs() == 0 { 1 } else { 0 }),
+        },
+        None => Timeval::default(),
+    };
+    setsockopt(
+        fd,
+        SOL_SOCKET,
+        optname,
+        &tv as *const Timeval as *const u8,
+        core::mem::size_of::<Timeval>(),
+    )?;
+    Ok(())
+}
+
 // ============================================================================
 // TcpListener
 // ============================================================================
@@ -568,6 +639,33 @@ impl TcpListener {
     }
 }
 
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> usize {
+        self.fd.raw()
+    }
+}
+
+impl IntoRawFd for TcpListener {
+    fn into_raw_fd(self) -> usize {
+        self.fd.into_raw()
+    }
+}
+
+impl FromRawFd for TcpListener {
+    unsafe fn from_raw_fd(fd: usize) -> Self {
+        TcpListener {
+            // SAFETY: forwarded from the caller.
+            fd: unsafe { SharedFd::from_raw(fd) },
+        }
+    }
+}
+
+impl From<OwnedFd> for TcpListener {
+    fn from(fd: OwnedFd) -> Self {
+        TcpListener { fd: fd.into() }
+    }
+}
+
 impl core::fmt::Debug for TcpListener {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("TcpListener").field("fd", &self.fd).finish()
@@ -677,6 +775,33 @@ impl UdpSocket {
     }
 }
 
+impl AsRawFd for UdpSocket {
+    fn as_raw_fd(&self) -> usize {
+        self.fd.raw()
+    }
+}
+
+impl IntoRawFd for UdpSocket {
+    fn into_raw_fd(self) -> usize {
+        self.fd.into_raw()
+    }
+}
+
+impl FromRawFd for UdpSocket {
+    unsafe fn from_raw_fd(fd: usize) -> Self {
+        UdpSocket {
+            // SAFETY: forwarded from the caller.
+            fd: unsafe { SharedFd::from_raw(fd) },
+        }
+    }
+}
+
+impl From<OwnedFd> for UdpSocket {
+    fn from(fd: OwnedFd) -> Self {
+        UdpSocket { fd: fd.into() }
+    }
+}
+
 impl core::fmt::Debug for UdpSocket {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("UdpSocket").field("fd", &self.fd).finish()
@@ -7,18 +7,27 @@
 //!
 //! VeridianOS passes environment variables on the initial stack following
 //! the Linux ABI convention: after argv comes a NULL-terminated envp array.
-//! The kernel also provides `SYS_PROCESS_GETENV (205)` for direct lookup.
+//! [`init`] copies argv and envp from there; the program's `_start` calls
+//! it with the initial stack pointer. [`setenv`] and [`unsetenv`] change
+//! only this copy, which a forked child shares and
+//! [`Command`](super::process::Command) passes to the programs it runs.
+//! Before `init`, lookups fall back to the kernel's copy of the environment
+//! via `SYS_PROCESS_GETENV (205)`.
 //!
 //! # Arguments
 //!
-//! Command-line arguments follow the standard C ABI: argc, argv on the stack.
+//! Command-line arguments follow the standard C ABI: argc, argv on the stack,
+//! and are available from [`args`] after [`init`].
 
 extern crate alloc;
 use alloc::{string::String, vec::Vec};
 
 use super::{
+    locks::Mutex,
     path::{OsStr, OsString, Path, PathBuf},
-    syscall0, syscall_result, SyscallError, SYS_GETEGID, SYS_GETEUID, SYS_GETGID, SYS_GETUID,
+    syscall0, syscall1, syscall2, syscall_result, AbiVersion, Errno, SyscallError, ABI_VERSION,
+    SYS_GETEGID, SYS_GETEUID, SYS_GETGID, SYS_GETGROUPS, SYS_GETUID, SYS_SETGID, SYS_SETGROUPS,
+    SYS_SETREGID, SYS_SETREUID, SYS_SETUID,
 };
 
 // ============================================================================
@@ -45,6 +54,49 @@ pub fn getegid() -> usize {
     unsafe { syscall0(SYS_GETEGID) as usize }
 }
 
+/// Set the user ID (all three IDs when privileged, otherwise only the
+/// effective UID, to the real or saved UID).
+pub fn setuid(uid: u32) -> Result<usize, SyscallError> {
+    syscall_result(unsafe { syscall1(SYS_SETUID, uid as usize) })
+}
+
+/// Set the group ID (same rules as [`setuid`]).
+pub fn setgid(gid: u32) -> Result<usize, SyscallError> {
+    syscall_result(unsafe { syscall1(SYS_SETGID, gid as usize) })
+}
+
+/// Set the real and effective user IDs. `None` leaves an ID unchanged.
+pub fn setreuid(ruid: Option<u32>, euid: Option<u32>) -> Result<usize, SyscallError> {
+    let ruid = ruid.unwrap_or(u32::MAX) as usize;
+    let euid = euid.unwrap_or(u32::MAX) as usize;
+    syscall_result(unsafe { syscall2(SYS_SETREUID, ruid, euid) })
+}
+
+/// Set the real and effective group IDs. `None` leaves an ID unchanged.
+pub fn setregid(rgid: Option<u32>, egid: Option<u32>) -> Result<usize, SyscallError> {
+    let rgid = rgid.unwrap_or(u32::MAX) as usize;
+    let egid = egid.unwrap_or(u32::MAX) as usize;
+    syscall_result(unsafe { syscall2(SYS_SETREGID, rgid, egid) })
+}
+
+/// Get the supplementary group list.
+pub fn getgroups() -> Result<Vec<u32>, SyscallError> {
+    let count = syscall_result(unsafe { syscall2(SYS_GETGROUPS, 0, 0) })?;
+    let mut list = alloc::vec![0u32; count];
+    if count > 0 {
+        let n = syscall_result(unsafe {
+            syscall2(SYS_GETGROUPS, list.len(), list.as_mut_ptr() as usize)
+        })?;
+        list.truncate(n);
+    }
+    Ok(list)
+}
+
+/// Replace the supplementary group list (requires privilege).
+pub fn setgroups(groups: &[u32]) -> Result<usize, SyscallError> {
+    syscall_result(unsafe { syscall2(SYS_SETGROUPS, groups.len(), groups.as_ptr() as usize) })
+}
+
 // ============================================================================
 // Environment Variables
 // ============================================================================
@@ -108,13 +160,136 @@ pub unsafe fn c_strlen(s: *const u8) -> usize {
     len
 }
 
-/// Look up an environment variable by name via the kernel's
-/// `SYS_PROCESS_GETENV (205)` syscall.
+/// Arguments and environment of the process, copied from the initial stack.
+#[derive(Default)]
+struct Startup {
+    args: Vec<OsString>,
+    vars: Vec<(OsString, OsString)>,
+}
+
+static STARTUP: Mutex<Option<Startup>> = Mutex::new(None);
+
+/// Copy argv and envp from the initial stack.
+///
+/// The stack holds argc, then argc argv pointers and a NULL, then the envp
+/// pointers and a NULL (followed by the aux vector, which is not read).
+///
+/// # Safety
+/// `sp` must be the stack pointer the kernel started the process with.
+pub unsafe fn init(sp: *const usize) {
+    let argc = unsafe { *sp };
+    let argv = unsafe { sp.add(1) } as *const *const u8;
+    let envp = unsafe { argv.add(argc + 1) };
+    // MALLOC_PROFILE, before the copies below allocate.
+    let profile = unsafe { getenv_from_envp(envp, b"MALLOC_PROFILE") };
+    if !profile.is_null() {
+        let value = unsafe { core::slice::from_raw_parts(profile, c_strlen(profile)) };
+        let _ = super::alloc::init_heap_profile(value);
+    }
+    let startup = Startup {
+        args: unsafe { args_from_argv(argc, argv) },
+        vars: unsafe { vars_from_envp(envp) },
+    };
+    *STARTUP.lock() = Some(startup);
+}
+
+/// The command-line arguments, starting with the program name. Empty
+/// before [`init`].
+pub fn args() -> Vec<OsString> {
+    STARTUP
+        .lock()
+        .as_ref()
+        .map(|startup| startup.args.clone())
+        .unwrap_or_default()
+}
+
+/// All environment variables, in the order the process received them.
+/// Empty before [`init`].
+pub fn vars() -> Vec<(OsString, OsString)> {
+    STARTUP
+        .lock()
+        .as_ref()
+        .map(|startup| startup.vars.clone())
+        .unwrap_or_default()
+}
+
+/// The environment as `KEY=VALUE` strings for a new program, or `None`
+/// before [`init`], in which case the kernel passes on its own copy.
+pub fn environ() -> Option<Vec<OsString>> {
+    let startup = STARTUP.lock();
+    let vars = &startup.as_ref()?.vars;
+    Some(vars.iter().map(|(key, val)| env_entry(key, val)).collect())
+}
+
+/// Format one `KEY=VALUE` environment entry.
+pub(crate) fn env_entry(key: &OsStr, val: &OsStr) -> OsString {
+    let mut entry = key.to_os_string();
+    entry.push(OsStr::from_bytes(b"="));
+    entry.push(val);
+    entry
+}
+
+/// Check that `name` can be used as an environment variable name.
+fn check_env_name(name: &OsStr) -> Result<(), Errno> {
+    if name.is_empty() || name.as_bytes().iter().any(|&b| b == b'=' || b == 0) {
+        Err(Errno::EINVAL)
+    } else {
+        Ok(())
+    }
+}
+
+/// Set an environment variable in this process's copy of the environment.
+///
+/// Fails with `EINVAL` if `name` is empty or contains `=` or NUL, or if
+/// `value` contains NUL.
+pub fn setenv(name: &OsStr, value: &OsStr) -> Result<(), Errno> {
+    check_env_name(name)?;
+    if value.as_bytes().contains(&0) {
+        return Err(Errno::EINVAL);
+    }
+    let mut startup = STARTUP.lock();
+    let startup = startup.get_or_insert_with(Startup::default);
+    match startup
+        .vars
+        .iter_mut()
+        .find(|(key, _)| key.as_os_str() == name)
+    {
+        Some((_, val)) => *val = value.to_os_string(),
+        None => startup
+            .vars
+            .push((name.to_os_string(), value.to_os_string())),
+    }
+    Ok(())
+}
+
+/// Remove an environment variable from this process's copy of the
+/// environment. Removing a variable that is not set is not an error.
+pub fn unsetenv(name: &OsStr) -> Result<(), Errno> {
+    check_env_name(name)?;
+    if let Some(startup) = STARTUP.lock().as_mut() {
+        startup.vars.retain(|(key, _)| key.as_os_str() != name);
+    }
+    Ok(())
+}
+
+/// Look up an environment variable by name.
+///
+/// Reads this process's copy of the environment once [`init`] has run (or
+/// [`setenv`] has been called), and the kernel's copy via
+/// `SYS_PROCESS_GETENV (205)` before that.
 ///
 /// Returns the value as an `OsString`, or `None` if not found.
 pub fn getenv(name: &OsStr) -> Option<OsString> {
     use super::{syscall4, SYS_PROCESS_GETENV};
 
+    if let Some(startup) = STARTUP.lock().as_ref() {
+        return startup
+            .vars
+            .iter()
+            .find(|(key, _)| key.as_os_str() == name)
+            .map(|(_, val)| val.clone());
+    }
+
     let mut buf = [0u8; 4096];
     let name_bytes = name.as_bytes();
 
@@ -223,18 +398,21 @@ pub fn set_current_dir(path: &Path) -> Result<(), SyscallError> {
 
 /// Get the home directory for the current user.
 ///
-/// Checks the `HOME` environment variable first.  Falls back to `/root`
-/// for uid 0 or `/home/<user>` otherwise (simplified, no /etc/passwd).
+/// Checks the `HOME` environment variable first, then the user's
+/// `/etc/passwd` entry. Falls back to `/root` for uid 0.
 pub fn home_dir() -> Option<PathBuf> {
     if let Some(home) = var("HOME") {
         return Some(PathBuf::from(home));
     }
-    // Fallback based on uid.
-    let uid = getuid();
+    let uid = getuid() as u32;
+    if let Some(user) = super::users::getpwuid(uid) {
+        if !user.home.is_empty() {
+            return Some(PathBuf::from_str(&user.home));
+        }
+    }
     if uid == 0 {
         Some(PathBuf::from_str("/root"))
     } else {
-        // Without /etc/passwd parsing, we cannot determine the username.
         None
     }
 }
@@ -253,6 +431,18 @@ pub fn kernel_info(buf: *mut u8) -> Result<usize, SyscallError> {
     syscall_result(ret)
 }
 
+/// Check that the running kernel supports the syscall ABI this library was
+/// built against, and return the kernel's ABI version.
+///
+/// Fails with `ENOTSUP` if the kernel cannot run this binary, or `ENOSYS`
+/// on kernels that predate the handshake.
+pub fn abi_handshake() -> Result<AbiVersion, Errno> {
+    use super::{syscall1, SYS_ABI_VERSION};
+
+    let ret = unsafe { syscall1(SYS_ABI_VERSION, ABI_VERSION.to_raw()) };
+    Errno::from_ret(ret).map(AbiVersion::from_raw)
+}
+
 // ============================================================================
 // Hostname
 // ============================================================================
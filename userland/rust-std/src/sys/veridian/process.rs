@@ -302,8 +302,10 @@ pub struct Command {
     program: OsString,
     /// Arguments (including argv[0] = program).
     args: Vec<OsString>,
-    /// Environment variables (KEY=VALUE).
-    env: Option<Vec<OsString>>,
+    /// Whether the child starts with an empty environment instead of ours.
+    env_clear: bool,
+    /// Variables set (`Some`) or removed (`None`) for the child.
+    env: Vec<(OsString, Option<OsString>)>,
     /// Working directory for the child.
     cwd: Option<PathBuf>,
     /// Stdin configuration.
@@ -320,7 +322,8 @@ impl Command {
         Command {
             program: program.to_os_string(),
             args: Vec::new(),
-            env: None,
+            env_clear: false,
+            env: Vec::new(),
             cwd: None,
             stdin_cfg: Stdio::Inherit,
             stdout_cfg: Stdio::Inherit,
@@ -352,13 +355,10 @@ impl Command {
         self
     }
 
-    /// Set an environment variable.
+    /// Set an environment variable for the child.
     pub fn env(&mut self, key: &OsStr, val: &OsStr) -> &mut Self {
-        let env = self.env.get_or_insert_with(Vec::new);
-        let mut entry = key.to_os_string();
-        entry.push(OsStr::from_bytes(b"="));
-        entry.push(val);
-        env.push(entry);
+        self.env
+            .push((key.to_os_string(), Some(val.to_os_string())));
         self
     }
 
@@ -367,12 +367,44 @@ impl Command {
         self.env(OsStr::new(key), OsStr::new(val))
     }
 
-    /// Clear all environment variables for the child.
+    /// Remove an environment variable for the child.
+    pub fn env_remove(&mut self, key: &OsStr) -> &mut Self {
+        self.env.push((key.to_os_string(), None));
+        self
+    }
+
+    /// Start the child with an empty environment instead of inheriting ours.
     pub fn env_clear(&mut self) -> &mut Self {
-        self.env = Some(Vec::new());
+        self.env_clear = true;
+        self.env.clear();
         self
     }
 
+    /// The child's environment as `KEY=VALUE` strings: this process's
+    /// environment (see [`super::os::environ`]) with the changes applied.
+    /// `None` leaves it to the kernel to pass on its own copy.
+    fn child_env(&self) -> Option<Vec<OsString>> {
+        let mut vars = if self.env_clear {
+            Vec::new()
+        } else {
+            match super::os::environ() {
+                Some(environ) => environ,
+                None if self.env.is_empty() => return None,
+                None => Vec::new(),
+            }
+        };
+        for (key, val) in &self.env {
+            vars.retain(|entry| {
+                let bytes = entry.as_bytes();
+                !(bytes.starts_with(key.as_bytes()) && bytes.get(key.len()) == Some(&b'='))
+            });
+            if let Some(val) = val {
+                vars.push(super::os::env_entry(key, val));
+            }
+        }
+        Some(vars)
+    }
+
     /// Set the working directory for the child.
     pub fn current_dir(&mut self, dir: &Path) -> &mut Self {
         self.cwd = Some(dir.to_path_buf());
@@ -417,10 +449,11 @@ impl Command {
         }
         argv_ptrs.push(core::ptr::null());
 
-        // Build envp.
+        // Build envp; a NULL envp inherits the kernel's copy.
+        let env = self.child_env();
         let mut envp_storage: Vec<Vec<u8>> = Vec::new();
         let mut envp_ptrs: Vec<*const u8> = Vec::new();
-        if let Some(env) = &self.env {
+        if let Some(env) = &env {
             for e in env {
                 let mut s = e.as_bytes().to_vec();
                 s.push(0);
@@ -429,8 +462,13 @@ impl Command {
             for s in &envp_storage {
                 envp_ptrs.push(s.as_ptr());
             }
+            envp_ptrs.push(core::ptr::null());
         }
-        envp_ptrs.push(core::ptr::null());
+        let envp = if env.is_some() {
+            envp_ptrs.as_ptr()
+        } else {
+            core::ptr::null()
+        };
 
         // Create pipes if needed.
         let stdin_pipe = match &self.stdin_cfg {
@@ -474,17 +512,18 @@ impl Command {
             }
 
             // Exec.
-            let _ = execve(prog_c.as_ptr(), argv_ptrs.as_ptr(), envp_ptrs.as_ptr());
+            let _ = execve(prog_c.as_ptr(), argv_ptrs.as_ptr(), envp);
 
             // If exec failed, exit with 127 (command not found convention).
             exit(127);
         }
 
         // --- Parent process ---
-        // Close the child's end of pipes and return parent's end.
-        let parent_stdin = stdin_pipe.map(|mut p| p.take_write_fd());
-        let parent_stdout = stdout_pipe.map(|mut p| p.take_read_fd());
-        let parent_stderr = stderr_pipe.map(|mut p| p.take_read_fd());
+        // Keep the parent's end of each pipe; the child's end is dropped
+        // (closed) here.
+        let parent_stdin = stdin_pipe.map(|p| p.into_fds().1);
+        let parent_stdout = stdout_pipe.map(|p| p.into_fds().0);
+        let parent_stderr = stderr_pipe.map(|p| p.into_fds().0);
 
         Ok(Child {
             pid: child_pid,
@@ -506,9 +545,8 @@ impl Command {
         for a in &self.args {
             cmd.arg(a.as_os_str());
         }
-        if let Some(ref env) = self.env {
-            cmd.env = Some(env.clone());
-        }
+        cmd.env_clear = self.env_clear;
+        cmd.env = self.env.clone();
         cmd.cwd = self.cwd.clone();
         cmd.stdin(Stdio::Inherit);
         cmd.stdout(Stdio::Piped);
@@ -0,0 +1,180 @@
+//! User and group database for VeridianOS.
+//!
+//! Parses `/etc/passwd` and `/etc/group` in the traditional colon-separated
+//! format:
+//!
+//! ```text
+//! name:passwd:uid:gid:gecos:home:shell
+//! name:passwd:gid:member1,member2,...
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored; malformed lines are
+//! skipped. Password hashes live in `/etc/shadow` and are not read here.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use super::{fd::OwnedFd, fs, SyscallError};
+
+/// Path of the user database (NUL-terminated).
+const PASSWD_PATH: &[u8] = b"/etc/passwd\0";
+
+/// Path of the group database (NUL-terminated).
+const GROUP_PATH: &[u8] = b"/etc/group\0";
+
+/// One `/etc/passwd` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passwd {
+    pub name: String,
+    pub passwd: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub gecos: String,
+    pub home: String,
+    pub shell: String,
+}
+
+/// One `/etc/group` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub passwd: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+fn is_comment_or_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Parse a single `/etc/passwd` line.
+pub fn parse_passwd_line(line: &str) -> Option<Passwd> {
+    if is_comment_or_blank(line) {
+        return None;
+    }
+    let mut fields = line.trim_end_matches(['\r', '\n']).split(':');
+    let name = fields.next()?;
+    let passwd = fields.next()?;
+    let uid = fields.next()?.parse().ok()?;
+    let gid = fields.next()?.parse().ok()?;
+    let gecos = fields.next()?;
+    let home = fields.next()?;
+    let shell = fields.next()?;
+    if name.is_empty() || fields.next().is_some() {
+        return None;
+    }
+    Some(Passwd {
+        name: String::from(name),
+        passwd: String::from(passwd),
+        uid,
+        gid,
+        gecos: String::from(gecos),
+        home: String::from(home),
+        shell: String::from(shell),
+    })
+}
+
+/// Parse a single `/etc/group` line.
+pub fn parse_group_line(line: &str) -> Option<Group> {
+    if is_comment_or_blank(line) {
+        return None;
+    }
+    let mut fields = line.trim_end_matches(['\r', '\n']).split(':');
+    let name = fields.next()?;
+    let passwd = fields.next()?;
+    let gid = fields.next()?.parse().ok()?;
+    let members = fields.next()?;
+    if name.is_empty() || fields.next().is_some() {
+        return None;
+    }
+    Some(Group {
+        name: String::from(name),
+        passwd: String::from(passwd),
+        gid,
+        members: members
+            .split(',')
+            .filter(|m| !m.is_empty())
+            .map(String::from)
+            .collect(),
+    })
+}
+
+/// Parse the full contents of an `/etc/passwd` file.
+pub fn parse_passwd(content: &str) -> Vec<Passwd> {
+    content.lines().filter_map(parse_passwd_line).collect()
+}
+
+/// Parse the full contents of an `/etc/group` file.
+pub fn parse_group(content: &str) -> Vec<Group> {
+    content.lines().filter_map(parse_group_line).collect()
+}
+
+// ============================================================================
+// Database Access
+// ============================================================================
+
+/// Read a whole (small) file into a string.
+fn read_text(path: &[u8]) -> Result<String, SyscallError> {
+    // SAFETY: `open` returned a new fd that nothing else owns.
+    let fd = unsafe { OwnedFd::from_raw(fs::open(path.as_ptr(), fs::O_RDONLY, 0)?) };
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 512];
+    loop {
+        match fd.read(&mut chunk)? {
+            0 => break,
+            n => data.extend_from_slice(&chunk[..n]),
+        }
+    }
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// All entries of `/etc/passwd`.
+pub fn users() -> Result<Vec<Passwd>, SyscallError> {
+    read_text(PASSWD_PATH).map(|text| parse_passwd(&text))
+}
+
+/// All entries of `/etc/group`.
+pub fn groups() -> Result<Vec<Group>, SyscallError> {
+    read_text(GROUP_PATH).map(|text| parse_group(&text))
+}
+
+/// Look up a user by name.
+pub fn getpwnam(name: &str) -> Option<Passwd> {
+    users().ok()?.into_iter().find(|u| u.name == name)
+}
+
+/// Look up a user by UID.
+pub fn getpwuid(uid: u32) -> Option<Passwd> {
+    users().ok()?.into_iter().find(|u| u.uid == uid)
+}
+
+/// Look up a group by name.
+pub fn getgrnam(name: &str) -> Option<Group> {
+    groups().ok()?.into_iter().find(|g| g.name == name)
+}
+
+/// Look up a group by GID.
+pub fn getgrgid(gid: u32) -> Option<Group> {
+    groups().ok()?.into_iter().find(|g| g.gid == gid)
+}
+
+/// Groups `user` belongs to: `base_gid` (normally the passwd primary group)
+/// followed by every group listing `user` as a member.
+///
+/// The result is suitable for `os::setgroups()` at login.
+pub fn grouplist(user: &str, base_gid: u32) -> Vec<u32> {
+    let mut list = Vec::from([base_gid]);
+    if let Ok(all) = groups() {
+        for group in all {
+            if group.gid != base_gid && group.members.iter().any(|m| m == user) {
+                list.push(group.gid);
+            }
+        }
+    }
+    list
+}
@@ -0,0 +1,585 @@
+//! The client handshake state machine and the application data stream.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{
+    handshake::{self, ClientHello, MessageBuffer},
+    keys::{self, Secret, HASH_LEN},
+    record::{self, ContentType, Protection, RawRecord, RecordBuffer, HEADER_LEN, MAX_FRAGMENT},
+    x509::{self, KeyAlgorithm, PublicKey},
+    CipherSuite, ClientConfig, Error, ServerName, Transport,
+};
+use crate::sys::veridian::{
+    crypto::{self, X25519_BASEPOINT},
+    time::{sleep_ms, Instant, SystemTime},
+    SyscallError,
+};
+
+/// Size of each transport read.
+const READ_CHUNK: usize = 4096;
+/// Poll interval when the transport reports `WouldBlock` mid-handshake.
+const POLL_INTERVAL_MS: u64 = 10;
+/// `close_notify` alert description.
+const CLOSE_NOTIFY: u8 = 0;
+/// Alert levels.
+const ALERT_WARNING: u8 = 1;
+const ALERT_FATAL: u8 = 2;
+
+/// A transport plus the record-level state both phases share.
+struct Conn<T: Transport> {
+    inner: T,
+    records: RecordBuffer,
+    read: Option<Protection>,
+    write: Option<Protection>,
+}
+
+impl<T: Transport> Conn<T> {
+    /// Receive the next record. With a `deadline` (the handshake),
+    /// `WouldBlock` is retried until it passes; otherwise it is returned so
+    /// the caller can poll.
+    fn read_record(&mut self, deadline: Option<Instant>) -> Result<RawRecord, Error> {
+        let mut chunk = [0u8; READ_CHUNK];
+        loop {
+            if let Some(rec) = self.records.pop()? {
+                return Ok(rec);
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(Error::TimedOut);
+            }
+            match self.inner.read(&mut chunk) {
+                Ok(0) => return Err(Error::Truncated),
+                Ok(n) => self.records.push(&chunk[..n]),
+                Err(SyscallError::WouldBlock) | Err(SyscallError::Interrupted)
+                    if deadline.is_some() =>
+                {
+                    let _ = sleep_ms(POLL_INTERVAL_MS);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Send `fragment` as one record, protected if write keys are set.
+    fn write_record(&mut self, content_type: ContentType, fragment: &[u8]) -> Result<(), Error> {
+        let rec = match self.write.as_mut() {
+            Some(protection) => protection.seal(content_type, fragment)?,
+            None => {
+                let mut rec = Vec::with_capacity(HEADER_LEN + fragment.len());
+                let initial = content_type == ContentType::Handshake;
+                rec.extend_from_slice(&record::header(content_type, fragment.len(), initial));
+                rec.extend_from_slice(fragment);
+                rec
+            }
+        };
+        self.inner.write_all(&rec)?;
+        Ok(())
+    }
+
+    fn send_alert(&mut self, level: u8, description: u8) -> Result<(), Error> {
+        self.write_record(ContentType::Alert, &[level, description])
+    }
+
+    /// Decrypt `rec` if read keys are set. A protected connection only
+    /// accepts `application_data` records on the wire.
+    fn unprotect(&mut self, rec: RawRecord) -> Result<(ContentType, Vec<u8>), Error> {
+        let outer = rec
+            .content_type()
+            .ok_or(Error::Protocol("unknown content type"))?;
+        match self.read.as_mut() {
+            Some(protection) if outer == ContentType::ApplicationData => {
+                protection.open(&rec.header, &rec.body)
+            }
+            Some(_) => Err(Error::Protocol("unprotected record after handshake keys")),
+            None => Ok((outer, rec.body)),
+        }
+    }
+}
+
+/// Interpret an alert record body.
+fn alert(body: &[u8]) -> Error {
+    match body {
+        [_, CLOSE_NOTIFY] => Error::Closed,
+        [_, description] => Error::Alert(*description),
+        _ => Error::Protocol("malformed alert"),
+    }
+}
+
+/// Handshake message body without its 4-byte header.
+fn body(msg: &[u8]) -> &[u8] {
+    &msg[4..]
+}
+
+/// Handshake-phase state: the transcript and reassembly buffer.
+struct Handshake<'c, T: Transport> {
+    conn: &'c mut Conn<T>,
+    messages: MessageBuffer,
+    transcript: Vec<u8>,
+    deadline: Option<Instant>,
+}
+
+impl<T: Transport> Handshake<'_, T> {
+    /// Next handshake message, which must be one of the `expected` types,
+    /// appended to the transcript.
+    fn next_message(&mut self, expected: &[u8]) -> Result<Vec<u8>, Error> {
+        loop {
+            if let Some(msg) = self.messages.pop()? {
+                if !expected.contains(&msg[0]) {
+                    return Err(Error::Protocol("unexpected handshake message"));
+                }
+                self.transcript.extend_from_slice(&msg);
+                return Ok(msg);
+            }
+            let rec = self.conn.read_record(self.deadline)?;
+            // Middlebox-compatibility change_cipher_spec is dropped
+            // unprotected, at any point in the handshake.
+            if rec.content_type() == Some(ContentType::ChangeCipherSpec) {
+                if rec.body != [1] {
+                    return Err(Error::Protocol("bad change_cipher_spec"));
+                }
+                continue;
+            }
+            match self.conn.unprotect(rec)? {
+                (ContentType::Handshake, data) if !data.is_empty() => self.messages.push(&data),
+                (ContentType::Alert, data) => {
+                    return Err(match alert(&data) {
+                        Error::Closed => Error::Protocol("close_notify during handshake"),
+                        e => e,
+                    })
+                }
+                _ => return Err(Error::Protocol("unexpected record during handshake")),
+            }
+        }
+    }
+
+    fn transcript_hash(&self) -> Result<[u8; HASH_LEN], Error> {
+        keys::transcript_hash(&self.transcript)
+    }
+
+    /// Keys must change on a record boundary (RFC 8446 section 5.1).
+    fn expect_record_boundary(&self) -> Result<(), Error> {
+        if self.messages.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Protocol("handshake message spans a key change"))
+        }
+    }
+}
+
+/// A TLS 1.3 connection over `T`.
+pub struct TlsStream<T: Transport> {
+    conn: Conn<T>,
+    suite: CipherSuite,
+    read_secret: Secret,
+    write_secret: Secret,
+    alpn: Option<Vec<u8>>,
+    /// Decrypted application data not yet returned.
+    plaintext: Vec<u8>,
+    pos: usize,
+    /// Post-handshake messages (tickets, key updates).
+    messages: MessageBuffer,
+    /// Peer sent `close_notify`.
+    eof: bool,
+    /// A fatal error ended the connection.
+    failed: bool,
+}
+
+impl<T: Transport> TlsStream<T> {
+    /// Run the handshake with `server_name` over `inner`.
+    ///
+    /// On failure a fatal alert is sent (best effort) and the transport is
+    /// dropped.
+    pub fn connect(inner: T, server_name: &str, config: &ClientConfig) -> Result<Self, Error> {
+        let server = ServerName::parse(server_name)?;
+        if config.suites.is_empty() {
+            return Err(Error::Unsupported("no cipher suites configured"));
+        }
+        let deadline = match config.handshake_timeout {
+            Some(timeout) => Some(Instant::now().checked_add(timeout).ok_or(Error::TimedOut)?),
+            None => None,
+        };
+        let mut conn = Conn {
+            inner,
+            records: RecordBuffer::default(),
+            read: None,
+            write: None,
+        };
+        match handshake(&mut conn, &server, config, deadline) {
+            Ok(done) => Ok(TlsStream {
+                conn,
+                suite: done.suite,
+                read_secret: done.read_secret,
+                write_secret: done.write_secret,
+                alpn: done.alpn,
+                plaintext: Vec::new(),
+                pos: 0,
+                messages: MessageBuffer::default(),
+                eof: false,
+                failed: false,
+            }),
+            Err(e) => {
+                if !matches!(e, Error::Io(_) | Error::TimedOut | Error::Alert(_)) {
+                    let _ = conn.send_alert(ALERT_FATAL, e.alert());
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// The negotiated cipher suite.
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.suite
+    }
+
+    /// The ALPN protocol the server selected, if any.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn.as_deref()
+    }
+
+    /// The underlying transport.
+    pub fn get_ref(&self) -> &T {
+        &self.conn.inner
+    }
+
+    /// Read decrypted application data, returning 0 after the peer's
+    /// `close_notify`.
+    ///
+    /// `WouldBlock` from the transport is passed through; partial records
+    /// stay buffered, so the call can simply be retried.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        loop {
+            if self.pos < self.plaintext.len() {
+                let n = buf.len().min(self.plaintext.len() - self.pos);
+                buf[..n].copy_from_slice(&self.plaintext[self.pos..self.pos + n]);
+                self.pos += n;
+                return Ok(n);
+            }
+            if self.eof {
+                return Ok(0);
+            }
+            if self.failed {
+                return Err(Error::Closed);
+            }
+            match self.read_next() {
+                Err(Error::Io(SyscallError::WouldBlock)) => {
+                    return Err(Error::Io(SyscallError::WouldBlock))
+                }
+                Err(e) => {
+                    self.fail(e);
+                    return Err(e);
+                }
+                Ok(()) => {}
+            }
+        }
+    }
+
+    /// Process one incoming record.
+    fn read_next(&mut self) -> Result<(), Error> {
+        let rec = self.conn.read_record(None)?;
+        match self.conn.unprotect(rec)? {
+            (ContentType::ApplicationData, data) => {
+                keys::zero(&mut self.plaintext);
+                self.plaintext = data;
+                self.pos = 0;
+            }
+            (ContentType::Handshake, data) if !data.is_empty() => {
+                self.messages.push(&data);
+                while let Some(msg) = self.messages.pop()? {
+                    self.post_handshake(&msg)?;
+                }
+            }
+            (ContentType::Alert, data) => match alert(&data) {
+                Error::Closed => self.eof = true,
+                e => return Err(e),
+            },
+            _ => return Err(Error::Protocol("unexpected record")),
+        }
+        Ok(())
+    }
+
+    /// Handle a post-handshake message.
+    fn post_handshake(&mut self, msg: &[u8]) -> Result<(), Error> {
+        match msg[0] {
+            // Resumption is not supported, so tickets are discarded.
+            handshake::NEW_SESSION_TICKET => Ok(()),
+            handshake::KEY_UPDATE => {
+                let update_requested = match body(msg) {
+                    [0] => false,
+                    [1] => true,
+                    _ => return Err(Error::Protocol("bad KeyUpdate")),
+                };
+                self.read_secret = keys::next_traffic_secret(&self.read_secret)?;
+                self.conn.read = Some(Protection::new(
+                    self.suite,
+                    keys::traffic_keys(self.suite, &self.read_secret)?,
+                ));
+                if update_requested {
+                    self.update_write_keys(false)?;
+                }
+                Ok(())
+            }
+            _ => Err(Error::Protocol("unexpected post-handshake message")),
+        }
+    }
+
+    /// Send a `KeyUpdate` and switch to the next write secret.
+    fn update_write_keys(&mut self, request_peer: bool) -> Result<(), Error> {
+        let msg = handshake::message(handshake::KEY_UPDATE, &[request_peer as u8]);
+        self.conn.write_record(ContentType::Handshake, &msg)?;
+        self.write_secret = keys::next_traffic_secret(&self.write_secret)?;
+        self.conn.write = Some(Protection::new(
+            self.suite,
+            keys::traffic_keys(self.suite, &self.write_secret)?,
+        ));
+        Ok(())
+    }
+
+    /// Rotate our traffic keys and ask the peer to rotate theirs.
+    pub fn key_update(&mut self) -> Result<(), Error> {
+        if self.failed {
+            return Err(Error::Closed);
+        }
+        self.update_write_keys(true).inspect_err(|&e| self.fail(e))
+    }
+
+    /// Encrypt and send all of `data`.
+    pub fn write_all(&mut self, data: &[u8]) -> Result<(), Error> {
+        if self.failed {
+            return Err(Error::Closed);
+        }
+        for fragment in data.chunks(MAX_FRAGMENT) {
+            if let Err(e) = self
+                .conn
+                .write_record(ContentType::ApplicationData, fragment)
+            {
+                self.fail(e);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Send `close_notify`. Reads continue to work until the peer closes.
+    pub fn close(&mut self) -> Result<(), Error> {
+        if self.failed {
+            return Err(Error::Closed);
+        }
+        self.conn.send_alert(ALERT_WARNING, CLOSE_NOTIFY)
+    }
+
+    /// Mark the connection dead, telling the peer why if the error was
+    /// ours to report.
+    fn fail(&mut self, e: Error) {
+        if !self.failed && !matches!(e, Error::Io(_) | Error::Alert(_) | Error::Truncated) {
+            let _ = self.conn.send_alert(ALERT_FATAL, e.alert());
+        }
+        self.failed = true;
+    }
+}
+
+impl<T: Transport> Transport for TlsStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, SyscallError> {
+        TlsStream::read(self, buf).map_err(to_syscall_error)
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> Result<(), SyscallError> {
+        TlsStream::write_all(self, data).map_err(to_syscall_error)
+    }
+}
+
+fn to_syscall_error(e: Error) -> SyscallError {
+    match e {
+        Error::Io(e) => e,
+        Error::TimedOut => SyscallError::TimedOut,
+        Error::Truncated => SyscallError::ConnectionReset,
+        _ => SyscallError::ConnectionAborted,
+    }
+}
+
+/// What a successful handshake hands to the stream.
+struct Established {
+    suite: CipherSuite,
+    read_secret: Secret,
+    write_secret: Secret,
+    alpn: Option<Vec<u8>>,
+}
+
+/// Run the full client handshake (RFC 8446 section 2, 1-RTT with X25519).
+fn handshake<T: Transport>(
+    conn: &mut Conn<T>,
+    server: &ServerName,
+    config: &ClientConfig,
+    deadline: Option<Instant>,
+) -> Result<Established, Error> {
+    let mut random = [0u8; 32];
+    let mut session_id = [0u8; 32];
+    let mut ephemeral = [0u8; 32];
+    crypto::getrandom(&mut random)?;
+    crypto::getrandom(&mut session_id)?;
+    crypto::getrandom(&mut ephemeral)?;
+    let x25519_public = crypto::x25519(&ephemeral, &X25519_BASEPOINT)?;
+
+    let hello = ClientHello {
+        random: &random,
+        session_id: &session_id,
+        suites: &config.suites,
+        x25519_public: &x25519_public,
+        server_name: match server {
+            ServerName::Dns(name) => Some(name.as_str()),
+            ServerName::Ip(_) => None,
+        },
+        alpn: &config.alpn,
+    }
+    .encode();
+    conn.write_record(ContentType::Handshake, &hello)?;
+
+    let mut hs = Handshake {
+        conn,
+        messages: MessageBuffer::default(),
+        transcript: hello,
+        deadline,
+    };
+
+    // ServerHello -> handshake traffic keys
+    let msg = hs.next_message(&[handshake::SERVER_HELLO])?;
+    let server_hello = handshake::parse_server_hello(body(&msg), &session_id, &config.suites)?;
+    hs.expect_record_boundary()?;
+    let suite = server_hello.suite;
+    let shared = crypto::x25519(&ephemeral, &server_hello.x25519_public);
+    keys::zero(&mut ephemeral);
+    let mut shared = shared.map_err(|_| Error::Protocol("invalid X25519 share"))?;
+    let secrets = keys::handshake_secrets(&shared, &hs.transcript_hash()?);
+    keys::zero(&mut shared);
+    let secrets = secrets?;
+    hs.conn.read = Some(Protection::new(
+        suite,
+        keys::traffic_keys(suite, &secrets.server)?,
+    ));
+
+    let msg = hs.next_message(&[handshake::ENCRYPTED_EXTENSIONS])?;
+    let alpn = handshake::parse_encrypted_extensions(body(&msg), &config.alpn)?.map(<[u8]>::to_vec);
+
+    // Optional CertificateRequest, then the server's chain
+    let mut cert_request_context = None;
+    let mut msg = hs.next_message(&[handshake::CERTIFICATE_REQUEST, handshake::CERTIFICATE])?;
+    if msg[0] == handshake::CERTIFICATE_REQUEST {
+        let mut r = handshake::Reader::new(body(&msg));
+        cert_request_context = Some(r.vec(1)?.to_vec());
+        msg = hs.next_message(&[handshake::CERTIFICATE])?;
+    }
+
+    let chain = handshake::parse_certificate(body(&msg))?;
+    let now = SystemTime::now().as_secs();
+    let leaf = x509::verify_chain(&chain, server, &config.roots, now)?;
+    let leaf_key = (leaf.public_key.algorithm, leaf.public_key.key.to_vec());
+    let verify_hash = hs.transcript_hash()?;
+
+    let msg = hs.next_message(&[handshake::CERTIFICATE_VERIFY])?;
+    let (scheme, signature) = handshake::parse_certificate_verify(body(&msg))?;
+    check_handshake_scheme(scheme, leaf_key.0, &leaf_key.1)?;
+    PublicKey {
+        algorithm: leaf_key.0,
+        key: &leaf_key.1,
+    }
+    .verify(
+        scheme,
+        &handshake::certificate_verify_content(&verify_hash),
+        signature,
+    )?;
+
+    let finished_hash = hs.transcript_hash()?;
+    let msg = hs.next_message(&[handshake::FINISHED])?;
+    let expected = keys::finished_mac(&secrets.server, &finished_hash)?;
+    if !constant_time_eq(body(&msg), &expected) {
+        return Err(Error::BadSignature);
+    }
+    hs.expect_record_boundary()?;
+    let app = secrets.application_secrets(&hs.transcript_hash()?)?;
+
+    // Client flight: compatibility CCS, optional empty Certificate, Finished
+    hs.conn.write_record(ContentType::ChangeCipherSpec, &[1])?;
+    hs.conn.write = Some(Protection::new(
+        suite,
+        keys::traffic_keys(suite, &secrets.client)?,
+    ));
+    if let Some(context) = cert_request_context {
+        let mut body = Vec::with_capacity(context.len() + 4);
+        body.push(context.len() as u8);
+        body.extend_from_slice(&context);
+        body.extend_from_slice(&[0, 0, 0]);
+        let msg = handshake::message(handshake::CERTIFICATE, &body);
+        hs.conn.write_record(ContentType::Handshake, &msg)?;
+        hs.transcript.extend_from_slice(&msg);
+    }
+    let mac = keys::finished_mac(&secrets.client, &hs.transcript_hash()?)?;
+    let msg = handshake::message(handshake::FINISHED, &mac);
+    hs.conn.write_record(ContentType::Handshake, &msg)?;
+
+    hs.conn.read = Some(Protection::new(
+        suite,
+        keys::traffic_keys(suite, &app.server)?,
+    ));
+    hs.conn.write = Some(Protection::new(
+        suite,
+        keys::traffic_keys(suite, &app.client)?,
+    ));
+    let keys::ApplicationSecrets { client, server } = app;
+    Ok(Established {
+        suite,
+        read_secret: server,
+        write_secret: client,
+        alpn,
+    })
+}
+
+/// Enforce the TLS 1.3 restrictions on `CertificateVerify` schemes: no
+/// PKCS#1 v1.5, and ECDSA schemes name their curve.
+fn check_handshake_scheme(scheme: u16, algorithm: KeyAlgorithm, key: &[u8]) -> Result<(), Error> {
+    let allowed = match scheme {
+        x509::ECDSA_SHA256 => key.len() == 65,
+        x509::ECDSA_SHA384 => key.len() == 97,
+        x509::RSA_PSS_SHA256 | x509::RSA_PSS_SHA384 | x509::RSA_PSS_SHA512 | x509::ED25519 => true,
+        _ => false,
+    };
+    if allowed && algorithm.supports(scheme) {
+        Ok(())
+    } else {
+        Err(Error::Protocol("CertificateVerify scheme not allowed"))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_handshake_scheme() {
+        let p256 = [4u8; 65];
+        assert!(check_handshake_scheme(x509::ECDSA_SHA256, KeyAlgorithm::Ecdsa, &p256).is_ok());
+        assert!(check_handshake_scheme(x509::ECDSA_SHA384, KeyAlgorithm::Ecdsa, &p256).is_err());
+        assert!(check_handshake_scheme(x509::RSA_PSS_SHA256, KeyAlgorithm::Rsa, &[]).is_ok());
+        assert!(check_handshake_scheme(x509::RSA_PKCS1_SHA256, KeyAlgorithm::Rsa, &[]).is_err());
+        assert!(check_handshake_scheme(x509::ED25519, KeyAlgorithm::Rsa, &[]).is_err());
+    }
+
+    #[test]
+    fn test_alert() {
+        assert_eq!(alert(&[1, 0]), Error::Closed);
+        assert_eq!(alert(&[2, 40]), Error::Alert(40));
+        assert!(matches!(alert(&[2]), Error::Protocol(_)));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
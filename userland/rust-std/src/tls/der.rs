@@ -0,0 +1,242 @@
+//! Minimal DER reader for X.509: definite-length TLVs, OIDs, BIT STRINGs
+//! and the two ASN.1 time types.
+
+use super::Error;
+
+/// Universal tags used by X.509.
+pub(crate) const TAG_BOOLEAN: u8 = 0x01;
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_UTC_TIME: u8 = 0x17;
+pub(crate) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+
+/// Context-specific constructed tag `[n]`.
+pub(crate) const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+const MALFORMED: Error = Error::BadCertificate("malformed DER");
+
+/// Cursor over a run of DER elements.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Der<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Der { input }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Tag of the next element, if any.
+    pub(crate) fn peek_tag(&self) -> Option<u8> {
+        self.input.first().copied()
+    }
+
+    /// Read the next element as `(tag, contents, whole encoding)`.
+    pub(crate) fn read_any(&mut self) -> Result<(u8, &'a [u8], &'a [u8]), Error> {
+        let input = self.input;
+        let tag = *input.first().ok_or(MALFORMED)?;
+        // Multi-byte tags never appear in the structures parsed here.
+        if tag & 0x1f == 0x1f {
+            return Err(MALFORMED);
+        }
+        let first = *input.get(1).ok_or(MALFORMED)? as usize;
+        let (len, header) = match first {
+            0..=0x7f => (first, 2),
+            0x81..=0x84 => {
+                let n = first - 0x80;
+                let bytes = input.get(2..2 + n).ok_or(MALFORMED)?;
+                let len = bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+                // DER requires the shortest length form.
+                if len < 0x80 || bytes[0] == 0 {
+                    return Err(MALFORMED);
+                }
+                (len, 2 + n)
+            }
+            _ => return Err(MALFORMED),
+        };
+        let end = header.checked_add(len).ok_or(MALFORMED)?;
+        let contents = input.get(header..end).ok_or(MALFORMED)?;
+        self.input = &input[end..];
+        Ok((tag, contents, &input[..end]))
+    }
+
+    /// Read an element that must have tag `tag`, returning its contents.
+    pub(crate) fn read(&mut self, tag: u8) -> Result<&'a [u8], Error> {
+        match self.read_any()? {
+            (t, contents, _) if t == tag => Ok(contents),
+            _ => Err(MALFORMED),
+        }
+    }
+
+    /// Read an element with tag `tag` if it is next.
+    pub(crate) fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>, Error> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Read a SEQUENCE and return a reader over its contents.
+    pub(crate) fn sequence(&mut self) -> Result<Der<'a>, Error> {
+        self.read(TAG_SEQUENCE).map(Der::new)
+    }
+
+    /// Read a BIT STRING with no unused bits.
+    pub(crate) fn bit_string(&mut self) -> Result<&'a [u8], Error> {
+        match self.read(TAG_BIT_STRING)? {
+            [0, bits @ ..] => Ok(bits),
+            _ => Err(MALFORMED),
+        }
+    }
+
+    /// Read a BOOLEAN.
+    pub(crate) fn boolean(&mut self) -> Result<bool, Error> {
+        match self.read(TAG_BOOLEAN)? {
+            [0x00] => Ok(false),
+            [0xff] => Ok(true),
+            _ => Err(MALFORMED),
+        }
+    }
+
+    /// Read a small non-negative INTEGER.
+    pub(crate) fn small_uint(&mut self) -> Result<u32, Error> {
+        let bytes = self.read(TAG_INTEGER)?;
+        if bytes.is_empty() || bytes.len() > 5 || bytes[0] & 0x80 != 0 {
+            return Err(MALFORMED);
+        }
+        let value = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        u32::try_from(value).map_err(|_| MALFORMED)
+    }
+
+    /// Read a UTCTime or GeneralizedTime as seconds since the Unix epoch.
+    pub(crate) fn time(&mut self) -> Result<u64, Error> {
+        match self.read_any()? {
+            (TAG_UTC_TIME, s, _) => parse_time(s, false),
+            (TAG_GENERALIZED_TIME, s, _) => parse_time(s, true),
+            _ => Err(MALFORMED),
+        }
+    }
+
+    /// Fail unless every element has been consumed.
+    pub(crate) fn finish(&self) -> Result<(), Error> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(MALFORMED)
+        }
+    }
+}
+
+/// Parse `YYMMDDHHMMSSZ` (UTCTime) or `YYYYMMDDHHMMSSZ` (GeneralizedTime),
+/// the only forms RFC 5280 allows.
+fn parse_time(s: &[u8], generalized: bool) -> Result<u64, Error> {
+    let digits = if generalized { 14 } else { 12 };
+    if s.len() != digits + 1 || s[digits] != b'Z' || !s[..digits].iter().all(u8::is_ascii_digit) {
+        return Err(MALFORMED);
+    }
+    let num = |i: usize| (s[i] - b'0') as u64 * 10 + (s[i + 1] - b'0') as u64;
+    let (year, rest) = if generalized {
+        (num(0) * 100 + num(2), 4)
+    } else {
+        // RFC 5280 section 4.1.2.5.1: 50-99 are 19xx, 00-49 are 20xx.
+        let yy = num(0);
+        (if yy >= 50 { 1900 + yy } else { 2000 + yy }, 2)
+    };
+    let (month, day) = (num(rest), num(rest + 2));
+    let (hour, minute, second) = (num(rest + 4), num(rest + 6), num(rest + 8));
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+        || year < 1970
+    {
+        return Err(MALFORMED);
+    }
+    let days = days_from_civil(year, month, day);
+    Ok(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Object identifiers used by X.509, as DER content octets.
+pub(crate) mod oid {
+    pub(crate) const RSA_ENCRYPTION: &[u8] =
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+    pub(crate) const SHA256_WITH_RSA: &[u8] =
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+    pub(crate) const SHA384_WITH_RSA: &[u8] =
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+    pub(crate) const SHA512_WITH_RSA: &[u8] =
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+    pub(crate) const EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+    pub(crate) const ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+    pub(crate) const ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+    pub(crate) const ECDSA_WITH_SHA512: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04];
+    pub(crate) const ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+    pub(crate) const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+    pub(crate) const KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+    pub(crate) const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+    pub(crate) const BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+    pub(crate) const CERTIFICATE_POLICIES: &[u8] = &[0x55, 0x1d, 0x20];
+    pub(crate) const EXT_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+    pub(crate) const ANY_EXT_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25, 0x00];
+    pub(crate) const SERVER_AUTH: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_tlv() {
+        let data = [0x30, 0x06, 0x02, 0x01, 0x05, 0x01, 0x01, 0xff, 0x05, 0x00];
+        let mut der = Der::new(&data);
+        let mut seq = der.sequence().unwrap();
+        assert_eq!(seq.small_uint(), Ok(5));
+        assert_eq!(seq.boolean(), Ok(true));
+        assert!(seq.finish().is_ok());
+        assert_eq!(der.read_optional(TAG_OID), Ok(None));
+        assert_eq!(der.read(TAG_NULL), Ok(&[][..]));
+        assert!(der.is_empty());
+
+        // Long-form length that fits in short form
+        assert!(Der::new(&[0x04, 0x81, 0x01, 0x00]).read_any().is_err());
+        // Truncated contents
+        assert!(Der::new(&[0x04, 0x05, 0x00]).read_any().is_err());
+    }
+
+    #[test]
+    fn test_time() {
+        let mut der = Der::new(b"\x17\x0d700101000000Z\x18\x0f20380119031408Z");
+        assert_eq!(der.time(), Ok(0));
+        assert_eq!(der.time(), Ok(0x7fff_ffff + 1));
+
+        // 2049 vs 1950 UTCTime windowing
+        assert_eq!(parse_time(b"491231235959Z", false), Ok(2_524_607_999));
+        assert_eq!(parse_time(b"500101000000Z", false), Err(MALFORMED));
+        assert!(parse_time(b"20240229120000+0100", true).is_err());
+        assert!(parse_time(b"20241301000000Z", true).is_err());
+    }
+}
@@ -0,0 +1,544 @@
+//! TLS 1.3 handshake messages (RFC 8446 section 4): `ClientHello`
+//! encoding, parsing of the server's flight, and reassembly of handshake
+//! messages from record payloads.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{CipherSuite, Error};
+
+// Handshake message types
+pub(crate) const CLIENT_HELLO: u8 = 1;
+pub(crate) const SERVER_HELLO: u8 = 2;
+pub(crate) const NEW_SESSION_TICKET: u8 = 4;
+pub(crate) const ENCRYPTED_EXTENSIONS: u8 = 8;
+pub(crate) const CERTIFICATE: u8 = 11;
+pub(crate) const CERTIFICATE_REQUEST: u8 = 13;
+pub(crate) const CERTIFICATE_VERIFY: u8 = 15;
+pub(crate) const FINISHED: u8 = 20;
+pub(crate) const KEY_UPDATE: u8 = 24;
+
+// Extension types
+const EXT_SERVER_NAME: u16 = 0;
+const EXT_SUPPORTED_GROUPS: u16 = 10;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+const EXT_ALPN: u16 = 16;
+const EXT_SUPPORTED_VERSIONS: u16 = 43;
+const EXT_KEY_SHARE: u16 = 51;
+
+/// The only key exchange group offered.
+pub(crate) const GROUP_X25519: u16 = 0x001d;
+pub(crate) const TLS13: u16 = 0x0304;
+pub(crate) const LEGACY_VERSION: u16 = 0x0303;
+
+/// Largest handshake message accepted (certificate chains included).
+pub(crate) const MAX_HANDSHAKE_MESSAGE: usize = 64 * 1024;
+
+/// Signature schemes offered in `signature_algorithms`, most preferred
+/// first. The PKCS#1 v1.5 entries are only acceptable in certificates.
+pub(crate) const SIGNATURE_SCHEMES: [u16; 10] = [
+    0x0403, 0x0503, 0x0603, 0x0807, 0x0804, 0x0805, 0x0806, 0x0401, 0x0501, 0x0601,
+];
+
+/// `ServerHello.random` of a `HelloRetryRequest`: SHA-256 of
+/// "HelloRetryRequest" (RFC 8446 section 4.1.3).
+const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+fn put_u16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+/// Append `body` with a `len_bytes`-byte big-endian length prefix.
+fn put_vec(out: &mut Vec<u8>, len_bytes: usize, body: &[u8]) {
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes()[4 - len_bytes..]);
+    out.extend_from_slice(body);
+}
+
+fn put_extension(out: &mut Vec<u8>, ext: u16, body: &[u8]) {
+    put_u16(out, ext);
+    put_vec(out, 2, body);
+}
+
+/// Wrap `body` in a handshake header of type `msg_type`.
+pub(crate) fn message(msg_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(4 + body.len());
+    out.push(msg_type);
+    put_vec(&mut out, 3, body);
+    out
+}
+
+/// Parameters of the `ClientHello`.
+pub(crate) struct ClientHello<'a> {
+    pub random: &'a [u8; 32],
+    /// Random legacy session ID, sent for middlebox compatibility.
+    pub session_id: &'a [u8; 32],
+    pub suites: &'a [CipherSuite],
+    pub x25519_public: &'a [u8; 32],
+    /// SNI host name; `None` for IP literals.
+    pub server_name: Option<&'a str>,
+    pub alpn: &'a [Vec<u8>],
+}
+
+impl ClientHello<'_> {
+    /// Encode as a complete handshake message.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(256);
+        put_u16(&mut body, LEGACY_VERSION);
+        body.extend_from_slice(self.random);
+        put_vec(&mut body, 1, self.session_id);
+        let suites: Vec<u8> = self
+            .suites
+            .iter()
+            .flat_map(|s| s.code().to_be_bytes())
+            .collect();
+        put_vec(&mut body, 2, &suites);
+        // legacy_compression_methods: null only
+        put_vec(&mut body, 1, &[0]);
+
+        let mut exts = Vec::with_capacity(192);
+        if let Some(name) = self.server_name {
+            let mut entry = Vec::with_capacity(name.len() + 3);
+            entry.push(0); // host_name
+            put_vec(&mut entry, 2, name.as_bytes());
+            let mut list = Vec::with_capacity(entry.len() + 2);
+            put_vec(&mut list, 2, &entry);
+            put_extension(&mut exts, EXT_SERVER_NAME, &list);
+        }
+        put_extension(&mut exts, EXT_SUPPORTED_VERSIONS, &[2, 0x03, 0x04]);
+        put_extension(&mut exts, EXT_SUPPORTED_GROUPS, &[0, 2, 0x00, 0x1d]);
+
+        let schemes: Vec<u8> = SIGNATURE_SCHEMES
+            .iter()
+            .flat_map(|s| s.to_be_bytes())
+            .collect();
+        let mut list = Vec::with_capacity(schemes.len() + 2);
+        put_vec(&mut list, 2, &schemes);
+        put_extension(&mut exts, EXT_SIGNATURE_ALGORITHMS, &list);
+
+        let mut share = Vec::with_capacity(36);
+        put_u16(&mut share, GROUP_X25519);
+        put_vec(&mut share, 2, self.x25519_public);
+        let mut shares = Vec::with_capacity(38);
+        put_vec(&mut shares, 2, &share);
+        put_extension(&mut exts, EXT_KEY_SHARE, &shares);
+
+        if !self.alpn.is_empty() {
+            let mut protocols = Vec::new();
+            for proto in self.alpn {
+                put_vec(&mut protocols, 1, proto);
+            }
+            let mut list = Vec::with_capacity(protocols.len() + 2);
+            put_vec(&mut list, 2, &protocols);
+            put_extension(&mut exts, EXT_ALPN, &list);
+        }
+
+        put_vec(&mut body, 2, &exts);
+        message(CLIENT_HELLO, &body)
+    }
+}
+
+// ============================================================================
+// Decoding
+// ============================================================================
+
+const DECODE: Error = Error::Protocol("malformed handshake message");
+
+/// Cursor over TLS presentation-language data.
+pub(crate) struct Reader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Reader { input }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.input.len() < n {
+            return Err(DECODE);
+        }
+        let (head, rest) = self.input.split_at(n);
+        self.input = rest;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    /// A vector with a `len_bytes`-byte length prefix.
+    pub(crate) fn vec(&mut self, len_bytes: usize) -> Result<&'a [u8], Error> {
+        let len = self
+            .take(len_bytes)?
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        self.take(len)
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), Error> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(DECODE)
+        }
+    }
+}
+
+/// Iterate `(type, body)` over an extensions block, rejecting duplicates.
+fn extensions(data: &[u8]) -> Result<Vec<(u16, &[u8])>, Error> {
+    let mut r = Reader::new(data);
+    let mut out: Vec<(u16, &[u8])> = Vec::new();
+    while !r.is_empty() {
+        let ext = r.u16()?;
+        let body = r.vec(2)?;
+        if out.iter().any(|(e, _)| *e == ext) {
+            return Err(Error::Protocol("duplicate extension"));
+        }
+        out.push((ext, body));
+    }
+    Ok(out)
+}
+
+/// The fields of a `ServerHello` the client acts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ServerHello {
+    pub suite: CipherSuite,
+    pub x25519_public: [u8; 32],
+}
+
+/// Parse a `ServerHello` body and check it against what was offered.
+pub(crate) fn parse_server_hello(
+    body: &[u8],
+    session_id: &[u8; 32],
+    offered: &[CipherSuite],
+) -> Result<ServerHello, Error> {
+    let mut r = Reader::new(body);
+    if r.u16()? != LEGACY_VERSION {
+        return Err(Error::Protocol("bad ServerHello version"));
+    }
+    let random = r.take(32)?;
+    if random == HELLO_RETRY_REQUEST_RANDOM {
+        // Only x25519 is offered, so a retry could only ask for a cookie or
+        // a group this client does not have.
+        return Err(Error::Unsupported("HelloRetryRequest"));
+    }
+    if r.vec(1)? != session_id {
+        return Err(Error::Protocol("session ID not echoed"));
+    }
+    let suite = CipherSuite::from_code(r.u16()?)
+        .filter(|s| offered.contains(s))
+        .ok_or(Error::Protocol("cipher suite not offered"))?;
+    if r.u8()? != 0 {
+        return Err(Error::Protocol("compression selected"));
+    }
+    let exts = r.vec(2)?;
+    r.finish()?;
+
+    let mut version = None;
+    let mut share = None;
+    for (ext, data) in extensions(exts)? {
+        let mut d = Reader::new(data);
+        match ext {
+            EXT_SUPPORTED_VERSIONS => version = Some(d.u16()?),
+            EXT_KEY_SHARE => {
+                if d.u16()? != GROUP_X25519 {
+                    return Err(Error::Protocol("key share group not offered"));
+                }
+                share = Some(d.vec(2)?);
+            }
+            _ => return Err(Error::Protocol("unexpected ServerHello extension")),
+        }
+        d.finish()?;
+    }
+
+    // Without supported_versions the server negotiated TLS 1.2 or older.
+    if version != Some(TLS13) {
+        return Err(Error::Unsupported("server does not speak TLS 1.3"));
+    }
+    let x25519_public = share
+        .and_then(|s| <[u8; 32]>::try_from(s).ok())
+        .ok_or(Error::Protocol("missing or bad key share"))?;
+    Ok(ServerHello {
+        suite,
+        x25519_public,
+    })
+}
+
+/// Parse `EncryptedExtensions`, returning the selected ALPN protocol.
+pub(crate) fn parse_encrypted_extensions<'a>(
+    body: &'a [u8],
+    offered_alpn: &[Vec<u8>],
+) -> Result<Option<&'a [u8]>, Error> {
+    let mut r = Reader::new(body);
+    let exts = r.vec(2)?;
+    r.finish()?;
+    let mut alpn = None;
+    for (ext, data) in extensions(exts)? {
+        match ext {
+            EXT_ALPN => {
+                let mut d = Reader::new(data);
+                let mut list = Reader::new(d.vec(2)?);
+                d.finish()?;
+                let proto = list.vec(1)?;
+                list.finish()?;
+                if !offered_alpn.iter().any(|p| p == proto) {
+                    return Err(Error::Protocol("ALPN protocol not offered"));
+                }
+                alpn = Some(proto);
+            }
+            EXT_SUPPORTED_VERSIONS | EXT_KEY_SHARE => {
+                return Err(Error::Protocol(
+                    "ServerHello extension in EncryptedExtensions",
+                ));
+            }
+            // server_name acknowledgements, max_fragment_length, etc.
+            _ => {}
+        }
+    }
+    Ok(alpn)
+}
+
+/// Parse a `Certificate` message into its DER certificates, leaf first.
+pub(crate) fn parse_certificate(body: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    let mut r = Reader::new(body);
+    if !r.vec(1)?.is_empty() {
+        return Err(Error::Protocol("certificate request context"));
+    }
+    let mut list = Reader::new(r.vec(3)?);
+    r.finish()?;
+    let mut certs = Vec::new();
+    while !list.is_empty() {
+        certs.push(list.vec(3)?);
+        // Per-certificate extensions (OCSP, SCT) are not used.
+        list.vec(2)?;
+    }
+    Ok(certs)
+}
+
+/// Parse `CertificateVerify` into `(scheme, signature)`.
+pub(crate) fn parse_certificate_verify(body: &[u8]) -> Result<(u16, &[u8]), Error> {
+    let mut r = Reader::new(body);
+    let scheme = r.u16()?;
+    let signature = r.vec(2)?;
+    r.finish()?;
+    Ok((scheme, signature))
+}
+
+/// Context string of the server's `CertificateVerify` signature.
+const SERVER_VERIFY_CONTEXT: &[u8] = b"TLS 1.3, server CertificateVerify";
+
+/// The content covered by the server's `CertificateVerify` signature
+/// (RFC 8446 section 4.4.3).
+pub(crate) fn certificate_verify_content(transcript_hash: &[u8]) -> Vec<u8> {
+    let mut content = Vec::with_capacity(64 + SERVER_VERIFY_CONTEXT.len() + 1 + 48);
+    content.resize(64, 0x20);
+    content.extend_from_slice(SERVER_VERIFY_CONTEXT);
+    content.push(0);
+    content.extend_from_slice(transcript_hash);
+    content
+}
+
+// ============================================================================
+// Reassembly
+// ============================================================================
+
+/// Splits handshake record payloads into whole messages. Messages may span
+/// records and one record may carry several.
+#[derive(Default)]
+pub(crate) struct MessageBuffer {
+    buf: Vec<u8>,
+}
+
+impl MessageBuffer {
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Bytes of an incomplete message still buffered.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Remove the next complete message, header included.
+    pub(crate) fn pop(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        if self.buf.len() < 4 {
+            return Ok(None);
+        }
+        let len = u32::from_be_bytes([0, self.buf[1], self.buf[2], self.buf[3]]) as usize;
+        if len > MAX_HANDSHAKE_MESSAGE {
+            return Err(Error::Protocol("handshake message too large"));
+        }
+        if self.buf.len() < 4 + len {
+            return Ok(None);
+        }
+        let rest = self.buf.split_off(4 + len);
+        Ok(Some(core::mem::replace(&mut self.buf, rest)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn server_hello(session_id: &[u8; 32], exts: &[u8]) -> Vec<u8> {
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[7; 32]);
+        put_vec(&mut body, 1, session_id);
+        put_u16(&mut body, 0x1303);
+        body.push(0);
+        put_vec(&mut body, 2, exts);
+        body
+    }
+
+    #[test]
+    fn test_client_hello() {
+        let alpn = [b"http/1.1".to_vec()];
+        let hello = ClientHello {
+            random: &[1; 32],
+            session_id: &[2; 32],
+            suites: &[
+                CipherSuite::Aes128GcmSha256,
+                CipherSuite::ChaCha20Poly1305Sha256,
+            ],
+            x25519_public: &[3; 32],
+            server_name: Some("pkg.veridian.dev"),
+            alpn: &alpn,
+        };
+        let msg = hello.encode();
+        assert_eq!(msg[0], CLIENT_HELLO);
+        assert_eq!(
+            u32::from_be_bytes([0, msg[1], msg[2], msg[3]]) as usize,
+            msg.len() - 4
+        );
+
+        let mut r = Reader::new(&msg[4..]);
+        assert_eq!(r.u16(), Ok(LEGACY_VERSION));
+        assert_eq!(r.take(32).unwrap(), &[1; 32]);
+        assert_eq!(r.vec(1).unwrap(), &[2; 32]);
+        assert_eq!(r.vec(2).unwrap(), &[0x13, 0x01, 0x13, 0x03]);
+        assert_eq!(r.vec(1).unwrap(), &[0]);
+        let exts = extensions(r.vec(2).unwrap()).unwrap();
+        assert!(r.finish().is_ok());
+
+        let ids: Vec<u16> = exts.iter().map(|(e, _)| *e).collect();
+        assert_eq!(
+            ids,
+            [
+                EXT_SERVER_NAME,
+                EXT_SUPPORTED_VERSIONS,
+                EXT_SUPPORTED_GROUPS,
+                EXT_SIGNATURE_ALGORITHMS,
+                EXT_KEY_SHARE,
+                EXT_ALPN
+            ]
+        );
+        assert!(exts[0].1.ends_with(b"pkg.veridian.dev"));
+        assert_eq!(&exts[4].1[6..], &[3; 32]);
+    }
+
+    #[test]
+    fn test_server_hello() {
+        let sid = [9; 32];
+        let offered = [CipherSuite::ChaCha20Poly1305Sha256];
+        let mut exts = vec![0x00, 0x2b, 0x00, 0x02, 0x03, 0x04];
+        exts.extend_from_slice(&[0x00, 0x33, 0x00, 0x24, 0x00, 0x1d, 0x00, 0x20]);
+        exts.extend_from_slice(&[5; 32]);
+
+        let hello = parse_server_hello(&server_hello(&sid, &exts), &sid, &offered).unwrap();
+        assert_eq!(hello.suite, CipherSuite::ChaCha20Poly1305Sha256);
+        assert_eq!(hello.x25519_public, [5; 32]);
+
+        assert_eq!(
+            parse_server_hello(&server_hello(&[0; 32], &exts), &sid, &offered),
+            Err(Error::Protocol("session ID not echoed"))
+        );
+        assert_eq!(
+            parse_server_hello(
+                &server_hello(&sid, &exts),
+                &sid,
+                &[CipherSuite::Aes128GcmSha256]
+            ),
+            Err(Error::Protocol("cipher suite not offered"))
+        );
+        // TLS 1.2 ServerHello: no supported_versions
+        assert_eq!(
+            parse_server_hello(&server_hello(&sid, &exts[6..]), &sid, &offered),
+            Err(Error::Unsupported("server does not speak TLS 1.3"))
+        );
+
+        let mut hrr = server_hello(&sid, &exts);
+        hrr[2..34].copy_from_slice(&HELLO_RETRY_REQUEST_RANDOM);
+        assert_eq!(
+            parse_server_hello(&hrr, &sid, &offered),
+            Err(Error::Unsupported("HelloRetryRequest"))
+        );
+    }
+
+    #[test]
+    fn test_encrypted_extensions() {
+        let offered = [b"http/1.1".to_vec()];
+        let body = [
+            0x00, 0x0f, 0x00, 0x10, 0x00, 0x0b, 0x00, 0x09, 0x08, b'h', b't', b't', b'p', b'/',
+            b'1', b'.', b'1',
+        ];
+        assert_eq!(
+            parse_encrypted_extensions(&body, &offered),
+            Ok(Some(&b"http/1.1"[..]))
+        );
+        assert!(parse_encrypted_extensions(&body, &[]).is_err());
+        assert_eq!(parse_encrypted_extensions(&[0, 0], &offered), Ok(None));
+    }
+
+    #[test]
+    fn test_certificate_messages() {
+        let body = [
+            0x00, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x02, 0xaa, 0xbb, 0x00, 0x00, 0x00, 0x00, 0x01,
+            0xcc, 0x00, 0x00,
+        ];
+        assert_eq!(
+            parse_certificate(&body).unwrap(),
+            [&[0xaa, 0xbb][..], &[0xcc][..]]
+        );
+        assert!(parse_certificate(&body[..body.len() - 1]).is_err());
+
+        assert_eq!(
+            parse_certificate_verify(&[0x08, 0x04, 0x00, 0x02, 0x11, 0x22]),
+            Ok((0x0804, &[0x11, 0x22][..]))
+        );
+        let content = certificate_verify_content(&[0xee; 32]);
+        assert_eq!(content.len(), 64 + 33 + 1 + 32);
+        assert_eq!(content[64 + 33], 0);
+    }
+
+    #[test]
+    fn test_message_buffer() {
+        let mut buf = MessageBuffer::default();
+        buf.push(&[FINISHED, 0, 0, 2, 0xaa]);
+        assert_eq!(buf.pop(), Ok(None));
+        buf.push(&[0xbb, KEY_UPDATE, 0, 0, 1, 0]);
+        assert_eq!(buf.pop(), Ok(Some(vec![FINISHED, 0, 0, 2, 0xaa, 0xbb])));
+        assert_eq!(buf.pop(), Ok(Some(vec![KEY_UPDATE, 0, 0, 1, 0])));
+        assert!(buf.is_empty());
+
+        buf.push(&[CERTIFICATE, 0xff, 0xff, 0xff]);
+        assert!(buf.pop().is_err());
+    }
+}
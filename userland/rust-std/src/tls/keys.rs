@@ -0,0 +1,222 @@
+//! TLS 1.3 key schedule (RFC 8446 section 7) over the kernel's HMAC.
+//!
+//! Both supported cipher suites use SHA-256, so every secret is 32 bytes.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{CipherSuite, Error};
+use crate::sys::veridian::crypto::{self, HashAlgorithm, AEAD_NONCE_LEN};
+
+/// Hash of the key schedule and transcript.
+pub(crate) const HASH: HashAlgorithm = HashAlgorithm::Sha256;
+/// Length of every secret and transcript hash.
+pub(crate) const HASH_LEN: usize = 32;
+
+/// A traffic or schedule secret, zeroed on drop.
+pub(crate) struct Secret(pub(crate) [u8; HASH_LEN]);
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        zero(&mut self.0);
+    }
+}
+
+/// Overwrite `buf` with zeros in a way the optimizer keeps.
+pub(crate) fn zero(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        // SAFETY: b is a valid, exclusive reference to a u8.
+        unsafe { core::ptr::write_volatile(b, 0) };
+    }
+}
+
+fn crypto_err(_: crate::sys::veridian::SyscallError) -> Error {
+    Error::Protocol("kernel crypto call failed")
+}
+
+fn to_secret(bytes: Vec<u8>) -> Result<Secret, Error> {
+    let mut out = [0u8; HASH_LEN];
+    if bytes.len() != HASH_LEN {
+        return Err(Error::Protocol("unexpected digest length"));
+    }
+    out.copy_from_slice(&bytes);
+    let mut bytes = bytes;
+    zero(&mut bytes);
+    Ok(Secret(out))
+}
+
+/// Hash of the handshake transcript so far.
+pub(crate) fn transcript_hash(transcript: &[u8]) -> Result<[u8; HASH_LEN], Error> {
+    let digest = crypto::hash(HASH, transcript).map_err(crypto_err)?;
+    let mut out = [0u8; HASH_LEN];
+    out.copy_from_slice(&digest);
+    Ok(out)
+}
+
+/// HKDF-Extract (RFC 5869).
+pub(crate) fn extract(salt: &[u8], ikm: &[u8]) -> Result<Secret, Error> {
+    let salt = if salt.is_empty() {
+        &[0u8; HASH_LEN][..]
+    } else {
+        salt
+    };
+    to_secret(crypto::hmac(HASH, salt, ikm).map_err(crypto_err)?)
+}
+
+/// The `HkdfLabel` structure for `label` and `context`.
+pub(crate) fn hkdf_label(len: usize, label: &[u8], context: &[u8]) -> Vec<u8> {
+    let mut info = Vec::with_capacity(4 + 6 + label.len() + context.len());
+    info.extend_from_slice(&(len as u16).to_be_bytes());
+    info.push((6 + label.len()) as u8);
+    info.extend_from_slice(b"tls13 ");
+    info.extend_from_slice(label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+    info
+}
+
+/// HKDF-Expand-Label, producing `len` bytes.
+pub(crate) fn expand_label(
+    secret: &Secret,
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+) -> Result<Vec<u8>, Error> {
+    let info = hkdf_label(len, label, context);
+    let mut out = Vec::with_capacity(len + HASH_LEN);
+    let mut block: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+    while out.len() < len {
+        let mut input = Vec::with_capacity(block.len() + info.len() + 1);
+        input.extend_from_slice(&block);
+        input.extend_from_slice(&info);
+        input.push(counter);
+        zero(&mut block);
+        block = crypto::hmac(HASH, &secret.0, &input).map_err(crypto_err)?;
+        out.extend_from_slice(&block);
+        counter += 1;
+    }
+    zero(&mut block);
+    out.truncate(len);
+    Ok(out)
+}
+
+/// Derive-Secret(secret, label, transcript hash).
+pub(crate) fn derive_secret(
+    secret: &Secret,
+    label: &[u8],
+    transcript_hash: &[u8; HASH_LEN],
+) -> Result<Secret, Error> {
+    to_secret(expand_label(secret, label, transcript_hash, HASH_LEN)?)
+}
+
+/// Key and IV for one direction of record protection.
+pub(crate) struct TrafficKeys {
+    pub key: Vec<u8>,
+    pub iv: [u8; AEAD_NONCE_LEN],
+}
+
+impl Drop for TrafficKeys {
+    fn drop(&mut self) {
+        zero(&mut self.key);
+        zero(&mut self.iv);
+    }
+}
+
+/// Expand a traffic secret into the record key and IV for `suite`.
+pub(crate) fn traffic_keys(suite: CipherSuite, secret: &Secret) -> Result<TrafficKeys, Error> {
+    let key = expand_label(secret, b"key", &[], suite.aead().key_len())?;
+    let mut iv = [0u8; AEAD_NONCE_LEN];
+    let mut iv_bytes = expand_label(secret, b"iv", &[], AEAD_NONCE_LEN)?;
+    iv.copy_from_slice(&iv_bytes);
+    zero(&mut iv_bytes);
+    Ok(TrafficKeys { key, iv })
+}
+
+/// The next-generation traffic secret after a `KeyUpdate`.
+pub(crate) fn next_traffic_secret(secret: &Secret) -> Result<Secret, Error> {
+    to_secret(expand_label(secret, b"traffic upd", &[], HASH_LEN)?)
+}
+
+/// `Finished.verify_data` for `base_key` (a handshake traffic secret)
+/// over the transcript hash.
+pub(crate) fn finished_mac(
+    base_key: &Secret,
+    transcript_hash: &[u8; HASH_LEN],
+) -> Result<Vec<u8>, Error> {
+    let finished_key = to_secret(expand_label(base_key, b"finished", &[], HASH_LEN)?)?;
+    crypto::hmac(HASH, &finished_key.0, transcript_hash).map_err(crypto_err)
+}
+
+/// Secrets produced by the handshake phase of the key schedule.
+pub(crate) struct HandshakeSecrets {
+    pub client: Secret,
+    pub server: Secret,
+    /// Handshake Secret, kept to derive the master secret.
+    handshake: Secret,
+}
+
+/// Secrets of the application phase.
+pub(crate) struct ApplicationSecrets {
+    pub client: Secret,
+    pub server: Secret,
+}
+
+/// Early and handshake phases: from the (EC)DHE shared secret and the
+/// transcript through `ServerHello`. No PSK is used.
+pub(crate) fn handshake_secrets(
+    shared_secret: &[u8],
+    hello_hash: &[u8; HASH_LEN],
+) -> Result<HandshakeSecrets, Error> {
+    let early = extract(&[], &[0u8; HASH_LEN])?;
+    let empty_hash = transcript_hash(&[])?;
+    let derived = derive_secret(&early, b"derived", &empty_hash)?;
+    let handshake = extract(&derived.0, shared_secret)?;
+    Ok(HandshakeSecrets {
+        client: derive_secret(&handshake, b"c hs traffic", hello_hash)?,
+        server: derive_secret(&handshake, b"s hs traffic", hello_hash)?,
+        handshake,
+    })
+}
+
+impl HandshakeSecrets {
+    /// Master secret and application traffic secrets, from the transcript
+    /// through the server `Finished`.
+    pub(crate) fn application_secrets(
+        &self,
+        server_finished_hash: &[u8; HASH_LEN],
+    ) -> Result<ApplicationSecrets, Error> {
+        let empty_hash = transcript_hash(&[])?;
+        let derived = derive_secret(&self.handshake, b"derived", &empty_hash)?;
+        let master = extract(&derived.0, &[0u8; HASH_LEN])?;
+        Ok(ApplicationSecrets {
+            client: derive_secret(&master, b"c ap traffic", server_finished_hash)?,
+            server: derive_secret(&master, b"s ap traffic", server_finished_hash)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hkdf_label() {
+        // RFC 8448 section 3: the "key" label for a 16-byte AES key
+        assert_eq!(
+            hkdf_label(16, b"key", &[]),
+            [0x00, 0x10, 0x09, b't', b'l', b's', b'1', b'3', b' ', b'k', b'e', b'y', 0x00]
+        );
+        let label = hkdf_label(32, b"c hs traffic", &[0xab; 32]);
+        assert_eq!(label[2] as usize, 6 + 12);
+        assert_eq!(label[3 + 18], 32);
+        assert_eq!(label.len(), 2 + 1 + 18 + 1 + 32);
+    }
+
+    #[test]
+    fn test_zero() {
+        let mut secret = Secret([0xaa; HASH_LEN]);
+        zero(&mut secret.0);
+        assert_eq!(secret.0, [0; HASH_LEN]);
+    }
+}
@@ -0,0 +1,303 @@
+//! TLS 1.3 client.
+//!
+//! A blocking TLS 1.3 (RFC 8446) client over any [`Transport`], built on the
+//! kernel's crypto syscalls rather than its own primitives:
+//!
+//! - key exchange: X25519 only
+//! - cipher suites: `TLS_AES_128_GCM_SHA256` and `TLS_CHACHA20_POLY1305_SHA256`
+//! - server authentication: X.509 chain validation against a [`RootStore`] (by
+//!   default the bundled roots), subjectAltName host matching, and
+//!   `CertificateVerify` with ECDSA P-256/P-384, RSA-PSS or Ed25519
+//! - SNI, ALPN, post-handshake `KeyUpdate`, `close_notify`
+//!
+//! Not supported: TLS 1.2 and older, session resumption and 0-RTT, client
+//! certificates (an empty `Certificate` is sent if the server asks), and
+//! `HelloRetryRequest`.
+//!
+//! ```rust,no_run
+//! use veridian_std::{
+//!     platform::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+//!     tls::{ClientConfig, RootStore, TlsStream},
+//! };
+//!
+//! let config = ClientConfig::new(RootStore::bundled().unwrap());
+//! let tcp = TcpStream::connect(&SocketAddr::new(
+//!     IpAddr::V4(Ipv4Addr::new(93, 184, 215, 14)),
+//!     443,
+//! ))
+//! .unwrap();
+//! let mut tls = TlsStream::connect(tcp, "example.com", &config).unwrap();
+//! tls.write_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+//!     .unwrap();
+//! ```
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use crate::sys::veridian::{crypto::Aead, time::Duration, SyscallError};
+
+mod client;
+mod der;
+mod handshake;
+mod keys;
+mod record;
+pub mod roots;
+pub mod x509;
+
+pub use client::TlsStream;
+pub use roots::{RootStore, TrustAnchor};
+
+pub use crate::http::Transport;
+
+/// Default bound on the whole handshake.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// TLS errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Socket error from the kernel.
+    Io(SyscallError),
+    /// The handshake did not complete within the configured timeout.
+    TimedOut,
+    /// The peer violated the protocol.
+    Protocol(&'static str),
+    /// The peer offered nothing this client supports.
+    Unsupported(&'static str),
+    /// The peer sent a fatal alert with this description code.
+    Alert(u8),
+    /// A record failed authentication.
+    BadRecordMac,
+    /// A certificate could not be parsed or is unusable.
+    BadCertificate(&'static str),
+    /// The chain does not lead to a trusted root.
+    UnknownIssuer,
+    /// The server certificate is outside its validity period.
+    CertificateExpired,
+    /// The server certificate does not name the requested host.
+    NameMismatch,
+    /// A certificate or `CertificateVerify` signature is invalid.
+    BadSignature,
+    /// The connection ended without `close_notify`, so data may be missing.
+    Truncated,
+    /// The connection was already closed or failed.
+    Closed,
+}
+
+impl From<SyscallError> for Error {
+    fn from(e: SyscallError) -> Self {
+        match e {
+            SyscallError::TimedOut => Error::TimedOut,
+            e => Error::Io(e),
+        }
+    }
+}
+
+impl Error {
+    /// Alert description sent to the peer when this error aborts the
+    /// connection (RFC 8446 section 6.2).
+    pub(crate) fn alert(self) -> u8 {
+        match self {
+            Error::BadRecordMac => 20,
+            Error::BadCertificate(_) | Error::NameMismatch => 42,
+            Error::CertificateExpired => 45,
+            Error::UnknownIssuer => 48,
+            Error::BadSignature => 51,
+            Error::Unsupported(_) => 40,
+            Error::Protocol(_) => 47,
+            // internal_error
+            _ => 80,
+        }
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::TimedOut => f.write_str("TLS handshake timed out"),
+            Error::Protocol(what) => write!(f, "TLS protocol error: {}", what),
+            Error::Unsupported(what) => write!(f, "unsupported TLS peer: {}", what),
+            Error::Alert(code) => write!(f, "TLS alert {} from peer", code),
+            Error::BadRecordMac => f.write_str("TLS record failed authentication"),
+            Error::BadCertificate(what) => write!(f, "bad certificate: {}", what),
+            Error::UnknownIssuer => f.write_str("certificate issuer is not trusted"),
+            Error::CertificateExpired => f.write_str("certificate expired or not yet valid"),
+            Error::NameMismatch => f.write_str("certificate does not match host name"),
+            Error::BadSignature => f.write_str("invalid signature"),
+            Error::Truncated => f.write_str("TLS connection truncated"),
+            Error::Closed => f.write_str("TLS connection closed"),
+        }
+    }
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// TLS 1.3 cipher suites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherSuite {
+    /// TLS_AES_128_GCM_SHA256 (0x1301)
+    Aes128GcmSha256,
+    /// TLS_CHACHA20_POLY1305_SHA256 (0x1303)
+    ChaCha20Poly1305Sha256,
+}
+
+impl CipherSuite {
+    /// Wire code point.
+    pub const fn code(self) -> u16 {
+        match self {
+            CipherSuite::Aes128GcmSha256 => 0x1301,
+            CipherSuite::ChaCha20Poly1305Sha256 => 0x1303,
+        }
+    }
+
+    /// Parse a wire code point.
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            0x1301 => Some(CipherSuite::Aes128GcmSha256),
+            0x1303 => Some(CipherSuite::ChaCha20Poly1305Sha256),
+            _ => None,
+        }
+    }
+
+    /// The record protection AEAD.
+    pub const fn aead(self) -> Aead {
+        match self {
+            CipherSuite::Aes128GcmSha256 => Aead::Aes128Gcm,
+            CipherSuite::ChaCha20Poly1305Sha256 => Aead::ChaCha20Poly1305,
+        }
+    }
+}
+
+/// The identity the server certificate must match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerName {
+    /// A DNS host name, sent as SNI.
+    Dns(String),
+    /// An IPv4 literal, matched against iPAddress SANs; no SNI is sent.
+    Ip([u8; 4]),
+}
+
+impl ServerName {
+    /// Parse a host name or dotted-quad IPv4 address.
+    pub fn parse(host: &str) -> Result<Self, Error> {
+        if let Some(ip) = parse_ipv4(host) {
+            return Ok(ServerName::Ip(ip));
+        }
+        let valid_label = |l: &str| {
+            !l.is_empty()
+                && l.len() <= 63
+                && l.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                && !l.starts_with('-')
+                && !l.ends_with('-')
+        };
+        let name = host.strip_suffix('.').unwrap_or(host);
+        if name.is_empty() || name.len() > 253 || !name.split('.').all(valid_label) {
+            return Err(Error::Unsupported("invalid server name"));
+        }
+        Ok(ServerName::Dns(name.to_ascii_lowercase()))
+    }
+}
+
+fn parse_ipv4(s: &str) -> Option<[u8; 4]> {
+    let mut out = [0u8; 4];
+    let mut parts = s.split('.');
+    for byte in out.iter_mut() {
+        let part = parts.next()?;
+        if part.is_empty() || part.len() > 3 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *byte = part.parse().ok()?;
+    }
+    parts.next().is_none().then_some(out)
+}
+
+/// Client settings shared by connections.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub(crate) roots: RootStore,
+    pub(crate) suites: Vec<CipherSuite>,
+    pub(crate) alpn: Vec<Vec<u8>>,
+    pub(crate) handshake_timeout: Option<Duration>,
+}
+
+impl ClientConfig {
+    /// Trust `roots`, offer both cipher suites (AES-GCM first), no ALPN,
+    /// and a 30-second handshake timeout.
+    pub fn new(roots: RootStore) -> Self {
+        ClientConfig {
+            roots,
+            suites: alloc::vec![
+                CipherSuite::Aes128GcmSha256,
+                CipherSuite::ChaCha20Poly1305Sha256,
+            ],
+            alpn: Vec::new(),
+            handshake_timeout: Some(DEFAULT_HANDSHAKE_TIMEOUT),
+        }
+    }
+
+    /// Offer `suites` in this order of preference.
+    pub fn with_cipher_suites(mut self, suites: &[CipherSuite]) -> Self {
+        self.suites = suites.to_vec();
+        self
+    }
+
+    /// Offer these ALPN protocol names, e.g. `b"http/1.1"`.
+    pub fn with_alpn(mut self, protocols: &[&[u8]]) -> Self {
+        self.alpn = protocols.iter().map(|p| p.to_vec()).collect();
+        self
+    }
+
+    /// Bound the handshake; `None` waits as long as the transport does.
+    pub fn with_handshake_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// The trusted roots.
+    pub fn roots(&self) -> &RootStore {
+        &self.roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_name() {
+        assert_eq!(
+            ServerName::parse("Pkg.Veridian.dev."),
+            Ok(ServerName::Dns("pkg.veridian.dev".into()))
+        );
+        assert_eq!(
+            ServerName::parse("10.0.2.2"),
+            Ok(ServerName::Ip([10, 0, 2, 2]))
+        );
+        assert!(ServerName::parse("").is_err());
+        assert!(ServerName::parse("bad_host").is_err());
+        assert!(ServerName::parse("-a.example").is_err());
+        // Not an IPv4 literal, so it must pass as a DNS name
+        assert_eq!(
+            ServerName::parse("1.2.3.256"),
+            Ok(ServerName::Dns("1.2.3.256".into()))
+        );
+    }
+
+    #[test]
+    fn test_cipher_suite_codes() {
+        for suite in [
+            CipherSuite::Aes128GcmSha256,
+            CipherSuite::ChaCha20Poly1305Sha256,
+        ] {
+            assert_eq!(CipherSuite::from_code(suite.code()), Some(suite));
+        }
+        // TLS_AES_256_GCM_SHA384 needs SHA-384 in the key schedule
+        assert_eq!(CipherSuite::from_code(0x1302), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! TLS record layer (RFC 8446 section 5): framing, AEAD protection with
+//! per-record nonces, and inner content type recovery.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{keys::TrafficKeys, CipherSuite, Error};
+use crate::sys::veridian::{
+    crypto::{self, AEAD_NONCE_LEN, AEAD_TAG_LEN},
+    SyscallError,
+};
+
+/// Largest plaintext fragment (2^14).
+pub(crate) const MAX_FRAGMENT: usize = 16384;
+/// Largest protected record body: fragment, content type, padding and tag.
+const MAX_CIPHERTEXT: usize = MAX_FRAGMENT + 256;
+/// Record header length.
+pub(crate) const HEADER_LEN: usize = 5;
+
+/// Record content types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentType {
+    ChangeCipherSpec = 20,
+    Alert = 21,
+    Handshake = 22,
+    ApplicationData = 23,
+}
+
+impl ContentType {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            20 => Some(ContentType::ChangeCipherSpec),
+            21 => Some(ContentType::Alert),
+            22 => Some(ContentType::Handshake),
+            23 => Some(ContentType::ApplicationData),
+            _ => None,
+        }
+    }
+}
+
+/// Record header for `body_len` bytes. The first `ClientHello` goes out as
+/// TLS 1.0 for compatibility; everything else claims TLS 1.2.
+pub(crate) fn header(content_type: ContentType, body_len: usize, initial: bool) -> [u8; 5] {
+    let len = (body_len as u16).to_be_bytes();
+    let minor = if initial { 0x01 } else { 0x03 };
+    [content_type as u8, 0x03, minor, len[0], len[1]]
+}
+
+/// One direction of record protection: keys plus sequence number.
+pub(crate) struct Protection {
+    suite: CipherSuite,
+    keys: TrafficKeys,
+    seq: u64,
+}
+
+impl Protection {
+    pub(crate) fn new(suite: CipherSuite, keys: TrafficKeys) -> Self {
+        Protection {
+            suite,
+            keys,
+            seq: 0,
+        }
+    }
+
+    /// Per-record nonce: the IV XORed with the padded sequence number.
+    fn nonce(&self) -> [u8; AEAD_NONCE_LEN] {
+        let mut nonce = self.keys.iv;
+        for (n, s) in nonce[AEAD_NONCE_LEN - 8..]
+            .iter_mut()
+            .zip(self.seq.to_be_bytes())
+        {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        // Wrapping would reuse a nonce; RFC 8446 requires a new key first.
+        self.seq = self
+            .seq
+            .checked_add(1)
+            .ok_or(Error::Protocol("record sequence number exhausted"))?;
+        Ok(())
+    }
+
+    /// Protect `fragment` of `content_type` as one complete record.
+    pub(crate) fn seal(
+        &mut self,
+        content_type: ContentType,
+        fragment: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut inner = Vec::with_capacity(fragment.len() + 1);
+        inner.extend_from_slice(fragment);
+        inner.push(content_type as u8);
+
+        let hdr = header(
+            ContentType::ApplicationData,
+            inner.len() + AEAD_TAG_LEN,
+            false,
+        );
+        let sealed = crypto::aead_seal(
+            self.suite.aead(),
+            &self.keys.key,
+            &self.nonce(),
+            &hdr,
+            &inner,
+        );
+        super::keys::zero(&mut inner);
+        let sealed = sealed.map_err(|_| Error::Protocol("record encryption failed"))?;
+        self.advance()?;
+
+        let mut record = Vec::with_capacity(HEADER_LEN + sealed.len());
+        record.extend_from_slice(&hdr);
+        record.extend_from_slice(&sealed);
+        Ok(record)
+    }
+
+    /// Authenticate and decrypt a protected record, returning the inner
+    /// content type and plaintext with padding removed.
+    pub(crate) fn open(
+        &mut self,
+        hdr: &[u8; HEADER_LEN],
+        body: &[u8],
+    ) -> Result<(ContentType, Vec<u8>), Error> {
+        let mut plain =
+            match crypto::aead_open(self.suite.aead(), &self.keys.key, &self.nonce(), hdr, body) {
+                Ok(plain) => plain,
+                Err(SyscallError::PermissionDenied) => return Err(Error::BadRecordMac),
+                Err(_) => return Err(Error::Protocol("record decryption failed")),
+            };
+        self.advance()?;
+
+        // Strip zero padding; the last non-zero byte is the real type.
+        let end = plain
+            .iter()
+            .rposition(|&b| b != 0)
+            .ok_or(Error::Protocol("record without content type"))?;
+        let content_type = ContentType::from_u8(plain[end])
+            .ok_or(Error::Protocol("unknown inner content type"))?;
+        plain.truncate(end);
+        if plain.len() > MAX_FRAGMENT {
+            return Err(Error::Protocol("record overflow"));
+        }
+        Ok((content_type, plain))
+    }
+}
+
+/// A record as received: header fields plus body.
+pub(crate) struct RawRecord {
+    pub header: [u8; HEADER_LEN],
+    pub body: Vec<u8>,
+}
+
+impl RawRecord {
+    pub(crate) fn content_type(&self) -> Option<ContentType> {
+        ContentType::from_u8(self.header[0])
+    }
+}
+
+/// Accumulates transport bytes and splits off whole records.
+#[derive(Default)]
+pub(crate) struct RecordBuffer {
+    buf: Vec<u8>,
+}
+
+impl RecordBuffer {
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Remove the next complete record, if one is buffered.
+    pub(crate) fn pop(&mut self) -> Result<Option<RawRecord>, Error> {
+        if self.buf.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        if ContentType::from_u8(self.buf[0]).is_none() || self.buf[1] != 0x03 {
+            return Err(Error::Protocol("not a TLS record"));
+        }
+        let len = u16::from_be_bytes([self.buf[3], self.buf[4]]) as usize;
+        if len > MAX_CIPHERTEXT {
+            return Err(Error::Protocol("record overflow"));
+        }
+        if self.buf.len() < HEADER_LEN + len {
+            return Ok(None);
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&self.buf[..HEADER_LEN]);
+        let body = self.buf[HEADER_LEN..HEADER_LEN + len].to_vec();
+        self.buf.drain(..HEADER_LEN + len);
+        Ok(Some(RawRecord { header, body }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        assert_eq!(
+            header(ContentType::Handshake, 0x0123, true),
+            [22, 3, 1, 0x01, 0x23]
+        );
+        assert_eq!(
+            header(ContentType::ApplicationData, 17, false),
+            [23, 3, 3, 0, 17]
+        );
+    }
+
+    #[test]
+    fn test_nonce() {
+        let mut p = Protection::new(
+            CipherSuite::Aes128GcmSha256,
+            TrafficKeys {
+                key: alloc::vec![0; 16],
+                iv: [0xff; AEAD_NONCE_LEN],
+            },
+        );
+        assert_eq!(p.nonce(), [0xff; AEAD_NONCE_LEN]);
+        p.seq = 0x0102;
+        let nonce = p.nonce();
+        assert_eq!(&nonce[..10], &[0xff; 10]);
+        assert_eq!(&nonce[10..], &[0xfe, 0xfd]);
+
+        p.seq = u64::MAX;
+        assert!(p.advance().is_err());
+    }
+
+    #[test]
+    fn test_record_buffer() {
+        let mut buf = RecordBuffer::default();
+        buf.push(&[22, 3, 3, 0, 3, 1, 2]);
+        assert!(buf.pop().unwrap().is_none());
+        buf.push(&[3, 20, 3, 3, 0, 1, 1]);
+        let rec = buf.pop().unwrap().unwrap();
+        assert_eq!(rec.content_type(), Some(ContentType::Handshake));
+        assert_eq!(rec.body, [1, 2, 3]);
+        let rec = buf.pop().unwrap().unwrap();
+        assert_eq!(rec.content_type(), Some(ContentType::ChangeCipherSpec));
+        assert!(buf.pop().unwrap().is_none());
+
+        let mut buf = RecordBuffer::default();
+        buf.push(b"HTTP/1.1 400 Bad Request\r\n");
+        assert!(buf.pop().is_err());
+        let mut buf = RecordBuffer::default();
+        buf.push(&[23, 3, 3, 0xff, 0xff]);
+        assert_eq!(buf.pop().err(), Some(Error::Protocol("record overflow")));
+    }
+}
@@ -0,0 +1,191 @@
+//! Trust anchors for certificate chain validation.
+//!
+//! The bundled store is `roots.pem`, generated by
+//! `scripts/update-tls-roots.sh` from a host CA directory. Only each root's
+//! subject name and public key are kept: anchors are trusted by
+//! configuration, so their own signatures, validity periods and extensions
+//! are not checked.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{
+    x509::{Certificate, KeyAlgorithm, PublicKey},
+    Error,
+};
+
+/// PEM bundle compiled into the library.
+pub(crate) const BUNDLED_ROOTS: &str = include_str!("roots.pem");
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// A trusted issuer: subject name and public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustAnchor {
+    subject: Vec<u8>,
+    algorithm: KeyAlgorithm,
+    key: Vec<u8>,
+}
+
+impl TrustAnchor {
+    /// Build an anchor from a DER certificate.
+    pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+        let cert = Certificate::parse(der)?;
+        Ok(TrustAnchor {
+            subject: cert.subject.to_vec(),
+            algorithm: cert.public_key.algorithm,
+            key: cert.public_key.key.to_vec(),
+        })
+    }
+
+    /// DER `Name` of the anchor.
+    pub fn subject(&self) -> &[u8] {
+        &self.subject
+    }
+
+    /// The anchor's public key.
+    pub fn public_key(&self) -> PublicKey<'_> {
+        PublicKey {
+            algorithm: self.algorithm,
+            key: &self.key,
+        }
+    }
+}
+
+/// A set of trust anchors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RootStore {
+    anchors: Vec<TrustAnchor>,
+}
+
+impl RootStore {
+    /// An empty store; every chain fails validation until roots are added.
+    pub const fn empty() -> Self {
+        RootStore {
+            anchors: Vec::new(),
+        }
+    }
+
+    /// The roots bundled with the library.
+    pub fn bundled() -> Result<Self, Error> {
+        let mut store = RootStore::empty();
+        store.add_pem(BUNDLED_ROOTS)?;
+        Ok(store)
+    }
+
+    /// Add a DER certificate as a trust anchor.
+    pub fn add_der(&mut self, der: &[u8]) -> Result<(), Error> {
+        let anchor = TrustAnchor::from_der(der)?;
+        if !self.anchors.contains(&anchor) {
+            self.anchors.push(anchor);
+        }
+        Ok(())
+    }
+
+    /// Add every certificate in a PEM bundle, e.g. a site-local CA read
+    /// from `/etc/ssl/certs`. Returns how many were added.
+    pub fn add_pem(&mut self, pem: &str) -> Result<usize, Error> {
+        let mut added = 0;
+        for der in pem_certificates(pem) {
+            self.add_der(&der?)?;
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// The anchors in insertion order.
+    pub fn anchors(&self) -> &[TrustAnchor] {
+        &self.anchors
+    }
+
+    pub fn len(&self) -> usize {
+        self.anchors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+}
+
+/// Decode the `CERTIFICATE` blocks of a PEM bundle; text outside the
+/// blocks is ignored.
+pub(crate) fn pem_certificates(pem: &str) -> impl Iterator<Item = Result<Vec<u8>, Error>> + '_ {
+    let mut rest = pem;
+    core::iter::from_fn(move || {
+        let start = rest.find(PEM_BEGIN)? + PEM_BEGIN.len();
+        let Some(len) = rest[start..].find(PEM_END) else {
+            rest = "";
+            return Some(Err(Error::BadCertificate("unterminated PEM block")));
+        };
+        let body = &rest[start..start + len];
+        rest = &rest[start + len + PEM_END.len()..];
+        Some(base64_decode(body).ok_or(Error::BadCertificate("invalid PEM base64")))
+    })
+}
+
+/// Decode standard base64, skipping whitespace.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    (padding <= 2).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(base64_decode("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(base64_decode("aGVs\nbG8h").unwrap(), b"hello!");
+        assert!(base64_decode("aGV*").is_none());
+        assert!(base64_decode("aG=Vs").is_none());
+    }
+
+    #[test]
+    fn test_pem_certificates() {
+        let pem = "junk\n-----BEGIN CERTIFICATE-----\naGk=\n-----END CERTIFICATE-----\n-----BEGIN \
+                   CERTIFICATE-----\nbw==\n-----END CERTIFICATE-----\n";
+        let certs: Vec<_> = pem_certificates(pem).collect();
+        assert_eq!(certs, [Ok(b"hi".to_vec()), Ok(b"o".to_vec())]);
+
+        let open = "-----BEGIN CERTIFICATE-----\naGk=\n";
+        assert!(pem_certificates(open).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_store_deduplicates() {
+        let mut store = RootStore::bundled().unwrap();
+        let count = store.len();
+        assert_eq!(store.add_pem(BUNDLED_ROOTS), Ok(count));
+        assert_eq!(store.len(), count);
+        assert!(RootStore::empty().is_empty());
+    }
+}
@@ -0,0 +1,437 @@
+//! X.509 certificate parsing and chain validation (RFC 5280 subset).
+//!
+//! Enough of RFC 5280 for Web PKI server certificates: validity periods,
+//! basic constraints with path length, key usage, extended key usage,
+//! subject alternative names and signature checks through the kernel's
+//! verifier. Name constraints and policy processing are not implemented, so
+//! a certificate marking either critical is rejected.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{
+    der::{self, oid, Der},
+    roots::RootStore,
+    Error, ServerName,
+};
+use crate::sys::veridian::crypto;
+
+/// Most intermediates accepted between the leaf and a trust anchor.
+pub const MAX_CHAIN_DEPTH: usize = 6;
+
+// TLS 1.3 SignatureScheme code points (RFC 8446 section 4.2.3), as taken by
+// `crypto::verify`.
+pub(crate) const RSA_PKCS1_SHA256: u16 = 0x0401;
+pub(crate) const RSA_PKCS1_SHA384: u16 = 0x0501;
+pub(crate) const RSA_PKCS1_SHA512: u16 = 0x0601;
+pub(crate) const ECDSA_SHA256: u16 = 0x0403;
+pub(crate) const ECDSA_SHA384: u16 = 0x0503;
+pub(crate) const ECDSA_SHA512: u16 = 0x0603;
+pub(crate) const RSA_PSS_SHA256: u16 = 0x0804;
+pub(crate) const RSA_PSS_SHA384: u16 = 0x0805;
+pub(crate) const RSA_PSS_SHA512: u16 = 0x0806;
+pub(crate) const ED25519: u16 = 0x0807;
+
+/// keyUsage bit 5 (keyCertSign), counted from the most significant bit.
+const KEY_CERT_SIGN: u8 = 0x04;
+
+/// Public key algorithm of a subjectPublicKeyInfo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    Rsa,
+    Ecdsa,
+    Ed25519,
+}
+
+impl KeyAlgorithm {
+    /// Can a key of this type produce signatures under `scheme`?
+    pub(crate) fn supports(self, scheme: u16) -> bool {
+        match self {
+            KeyAlgorithm::Rsa => matches!(
+                scheme,
+                RSA_PKCS1_SHA256
+                    | RSA_PKCS1_SHA384
+                    | RSA_PKCS1_SHA512
+                    | RSA_PSS_SHA256
+                    | RSA_PSS_SHA384
+                    | RSA_PSS_SHA512
+            ),
+            KeyAlgorithm::Ecdsa => matches!(scheme, ECDSA_SHA256 | ECDSA_SHA384 | ECDSA_SHA512),
+            KeyAlgorithm::Ed25519 => scheme == ED25519,
+        }
+    }
+}
+
+/// A subject public key in the form `crypto::verify` takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey<'a> {
+    pub algorithm: KeyAlgorithm,
+    /// RSAPublicKey DER, SEC1 point or raw Ed25519 key.
+    pub key: &'a [u8],
+}
+
+impl PublicKey<'_> {
+    /// Verify `signature` over `message` made with `scheme`.
+    pub(crate) fn verify(
+        &self,
+        scheme: u16,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        if !self.algorithm.supports(scheme) {
+            return Err(Error::BadSignature);
+        }
+        match crypto::verify(scheme, self.key, message, signature) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::BadSignature),
+            Err(_) => Err(Error::BadCertificate("unusable public key")),
+        }
+    }
+}
+
+/// A parsed certificate borrowing from its DER encoding.
+#[derive(Debug, Clone)]
+pub struct Certificate<'a> {
+    /// The signed `tbsCertificate`, tag and length included.
+    tbs: &'a [u8],
+    /// Signature scheme of the issuer's signature, if supported. Trust
+    /// anchors are often self-signed with SHA-1, which is never checked.
+    signature_scheme: Option<u16>,
+    signature: &'a [u8],
+    /// Raw DER `Name`s, compared byte-for-byte when chaining.
+    pub issuer: &'a [u8],
+    pub subject: &'a [u8],
+    /// Validity period, seconds since the Unix epoch.
+    pub not_before: u64,
+    pub not_after: u64,
+    pub public_key: PublicKey<'a>,
+    /// basicConstraints cA.
+    pub is_ca: bool,
+    /// basicConstraints pathLenConstraint.
+    pub path_len: Option<u32>,
+    /// keyUsage permits keyCertSign (or keyUsage is absent).
+    key_cert_sign: bool,
+    /// extendedKeyUsage permits serverAuth (or is absent).
+    server_auth: bool,
+    /// subjectAltName dNSName entries.
+    dns_names: Vec<&'a [u8]>,
+    /// subjectAltName iPAddress entries.
+    ip_addresses: Vec<&'a [u8]>,
+}
+
+impl<'a> Certificate<'a> {
+    /// Parse a DER certificate.
+    pub fn parse(der: &'a [u8]) -> Result<Self, Error> {
+        let mut outer = Der::new(der);
+        let mut cert = outer.sequence()?;
+        outer.finish()?;
+
+        let (_, _, tbs) = cert.read_any()?;
+        let signature_alg = algorithm_identifier(&mut cert)?;
+        let signature = cert.bit_string()?;
+        cert.finish()?;
+        let signature_scheme = signature_scheme(signature_alg);
+
+        let mut tbs_fields = Der::new(tbs).sequence()?;
+        // version [0] EXPLICIT; only v3 carries extensions, v1 roots remain
+        // usable as trust anchors.
+        let version = match tbs_fields.read_optional(der::context(0))? {
+            Some(v) => Der::new(v).small_uint()?,
+            None => 0,
+        };
+        tbs_fields.read(der::TAG_INTEGER)?;
+        if algorithm_identifier(&mut tbs_fields)? != signature_alg {
+            return Err(Error::BadCertificate("signature algorithm mismatch"));
+        }
+        let (_, _, issuer) = tbs_fields.read_any()?;
+        let mut validity = tbs_fields.sequence()?;
+        let not_before = validity.time()?;
+        let not_after = validity.time()?;
+        validity.finish()?;
+        let (_, _, subject) = tbs_fields.read_any()?;
+        let public_key = subject_public_key(&mut tbs_fields)?;
+
+        let mut cert = Certificate {
+            tbs,
+            signature_scheme,
+            signature,
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            public_key,
+            is_ca: false,
+            path_len: None,
+            key_cert_sign: true,
+            server_auth: true,
+            dns_names: Vec::new(),
+            ip_addresses: Vec::new(),
+        };
+
+        // issuerUniqueID [1] and subjectUniqueID [2] are skipped.
+        tbs_fields.read_optional(0x81)?;
+        tbs_fields.read_optional(0x82)?;
+        if let Some(extensions) = tbs_fields.read_optional(der::context(3))? {
+            if version != 2 {
+                return Err(Error::BadCertificate("extensions in a v1 certificate"));
+            }
+            cert.parse_extensions(extensions)?;
+        }
+        tbs_fields.finish()?;
+        Ok(cert)
+    }
+
+    fn parse_extensions(&mut self, extensions: &'a [u8]) -> Result<(), Error> {
+        let mut list = Der::new(extensions).sequence()?;
+        while !list.is_empty() {
+            let mut ext = list.sequence()?;
+            let id = ext.read(der::TAG_OID)?;
+            let critical = match ext.peek_tag() {
+                Some(der::TAG_BOOLEAN) => ext.boolean()?,
+                _ => false,
+            };
+            let value = ext.read(der::TAG_OCTET_STRING)?;
+            ext.finish()?;
+
+            match id {
+                oid::BASIC_CONSTRAINTS => {
+                    let mut bc = Der::new(value).sequence()?;
+                    if bc.peek_tag() == Some(der::TAG_BOOLEAN) {
+                        self.is_ca = bc.boolean()?;
+                    }
+                    if bc.peek_tag() == Some(der::TAG_INTEGER) {
+                        self.path_len = Some(bc.small_uint()?);
+                    }
+                    bc.finish()?;
+                }
+                oid::KEY_USAGE => {
+                    let bits = Der::new(value).read(der::TAG_BIT_STRING)?;
+                    // First octet is the unused-bit count
+                    self.key_cert_sign = bits.get(1).is_some_and(|b| b & KEY_CERT_SIGN != 0);
+                }
+                oid::EXT_KEY_USAGE => {
+                    let mut purposes = Der::new(value).sequence()?;
+                    self.server_auth = false;
+                    while !purposes.is_empty() {
+                        let purpose = purposes.read(der::TAG_OID)?;
+                        if purpose == oid::SERVER_AUTH || purpose == oid::ANY_EXT_KEY_USAGE {
+                            self.server_auth = true;
+                        }
+                    }
+                }
+                oid::SUBJECT_ALT_NAME => {
+                    let mut names = Der::new(value).sequence()?;
+                    while !names.is_empty() {
+                        match names.read_any()? {
+                            (0x82, name, _) => self.dns_names.push(name),
+                            (0x87, addr, _) => self.ip_addresses.push(addr),
+                            _ => {}
+                        }
+                    }
+                }
+                // Policies constrain nothing for a client that accepts any
+                // policy.
+                oid::CERTIFICATE_POLICIES => {}
+                _ if critical => {
+                    return Err(Error::BadCertificate("unsupported critical extension"));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Is `now` within the validity period?
+    pub fn is_valid_at(&self, now: u64) -> bool {
+        (self.not_before..=self.not_after).contains(&now)
+    }
+
+    /// Does the certificate name `server`?
+    ///
+    /// Only subjectAltName is consulted (RFC 6125 section 6.4.4 permits the
+    /// common name fallback, but current Web PKI certificates never need it).
+    pub fn matches_name(&self, server: &ServerName) -> bool {
+        match server {
+            ServerName::Dns(host) => self
+                .dns_names
+                .iter()
+                .any(|pattern| dns_name_matches(pattern, host.as_bytes())),
+            ServerName::Ip(addr) => self.ip_addresses.iter().any(|a| a == addr),
+        }
+    }
+
+    /// Check that `issuer` signed this certificate.
+    pub(crate) fn verify_signed_by(&self, issuer: &PublicKey<'_>) -> Result<(), Error> {
+        let scheme = self
+            .signature_scheme
+            .ok_or(Error::BadCertificate("unsupported signature algorithm"))?;
+        issuer.verify(scheme, self.tbs, self.signature)
+    }
+}
+
+/// Read an `AlgorithmIdentifier` and return its OID. Parameters are not
+/// interpreted.
+fn algorithm_identifier<'a>(der: &mut Der<'a>) -> Result<&'a [u8], Error> {
+    let mut alg = der.sequence()?;
+    alg.read(der::TAG_OID)
+}
+
+fn signature_scheme(alg: &[u8]) -> Option<u16> {
+    Some(match alg {
+        oid::SHA256_WITH_RSA => RSA_PKCS1_SHA256,
+        oid::SHA384_WITH_RSA => RSA_PKCS1_SHA384,
+        oid::SHA512_WITH_RSA => RSA_PKCS1_SHA512,
+        oid::ECDSA_WITH_SHA256 => ECDSA_SHA256,
+        oid::ECDSA_WITH_SHA384 => ECDSA_SHA384,
+        oid::ECDSA_WITH_SHA512 => ECDSA_SHA512,
+        oid::ED25519 => ED25519,
+        _ => return None,
+    })
+}
+
+fn subject_public_key<'a>(der: &mut Der<'a>) -> Result<PublicKey<'a>, Error> {
+    let mut spki = der.sequence()?;
+    let algorithm = match algorithm_identifier(&mut spki)? {
+        oid::RSA_ENCRYPTION => KeyAlgorithm::Rsa,
+        oid::EC_PUBLIC_KEY => KeyAlgorithm::Ecdsa,
+        oid::ED25519 => KeyAlgorithm::Ed25519,
+        _ => return Err(Error::BadCertificate("unsupported public key algorithm")),
+    };
+    let key = spki.bit_string()?;
+    spki.finish()?;
+    Ok(PublicKey { algorithm, key })
+}
+
+/// RFC 6125 matching of a dNSName `pattern` against `host` (both ASCII,
+/// compared case-insensitively). A wildcard may only form the whole
+/// leftmost label and must be followed by at least two labels.
+fn dns_name_matches(pattern: &[u8], host: &[u8]) -> bool {
+    let pattern = pattern.strip_suffix(b".").unwrap_or(pattern);
+    let host = host.strip_suffix(b".").unwrap_or(host);
+    if let Some(suffix) = pattern.strip_prefix(b"*.") {
+        if !suffix.contains(&b'.') || suffix.contains(&b'*') {
+            return false;
+        }
+        return match host.iter().position(|&b| b == b'.') {
+            Some(dot) if dot > 0 => host[dot + 1..].eq_ignore_ascii_case(suffix),
+            _ => false,
+        };
+    }
+    !pattern.contains(&b'*') && pattern.eq_ignore_ascii_case(host)
+}
+
+/// Validate the server's `chain` (leaf first, as sent in the TLS
+/// `Certificate` message) for `server` at time `now`, and return the leaf.
+///
+/// Intermediates may arrive in any order; unused extras are ignored. The
+/// path ends at the first certificate whose issuer is a trust anchor in
+/// `roots` and whose signature that anchor verifies.
+pub(crate) fn verify_chain<'a>(
+    chain: &[&'a [u8]],
+    server: &ServerName,
+    roots: &RootStore,
+    now: u64,
+) -> Result<Certificate<'a>, Error> {
+    let leaf_der = chain.first().ok_or(Error::BadCertificate("empty chain"))?;
+    let leaf = Certificate::parse(leaf_der)?;
+    if !leaf.is_valid_at(now) {
+        return Err(Error::CertificateExpired);
+    }
+    if !leaf.server_auth {
+        return Err(Error::BadCertificate("not valid for server authentication"));
+    }
+    if !leaf.matches_name(server) {
+        return Err(Error::NameMismatch);
+    }
+
+    let intermediates = chain[1..]
+        .iter()
+        .map(|der| Certificate::parse(der))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut current = leaf.clone();
+    let mut used = Vec::new();
+    for depth in 0..=MAX_CHAIN_DEPTH {
+        for anchor in roots.anchors() {
+            if anchor.subject() == current.issuer
+                && current.verify_signed_by(&anchor.public_key()).is_ok()
+            {
+                return Ok(leaf);
+            }
+        }
+        if depth == MAX_CHAIN_DEPTH {
+            break;
+        }
+
+        // `depth` intermediates sit below the candidate, so its
+        // pathLenConstraint must allow that many.
+        let next = intermediates.iter().enumerate().find(|(i, cand)| {
+            !used.contains(i)
+                && cand.subject == current.issuer
+                && cand.is_ca
+                && cand.key_cert_sign
+                && cand.server_auth
+                && cand.path_len.is_none_or(|max| depth <= max as usize)
+                && cand.is_valid_at(now)
+                && current.verify_signed_by(&cand.public_key).is_ok()
+        });
+        match next {
+            Some((i, cand)) => {
+                used.push(i);
+                current = cand.clone();
+            }
+            None => break,
+        }
+    }
+    Err(Error::UnknownIssuer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_name_matches() {
+        assert!(dns_name_matches(b"example.com", b"EXAMPLE.com"));
+        assert!(dns_name_matches(b"example.com.", b"example.com"));
+        assert!(dns_name_matches(b"*.example.com", b"pkg.example.com"));
+        assert!(!dns_name_matches(b"*.example.com", b"example.com"));
+        assert!(!dns_name_matches(b"*.example.com", b"a.b.example.com"));
+        assert!(!dns_name_matches(b"*.com", b"example.com"));
+        assert!(!dns_name_matches(b"p*.example.com", b"pkg.example.com"));
+        assert!(!dns_name_matches(b"example.com", b"example.org"));
+    }
+
+    #[test]
+    fn test_parse_bundled_roots() {
+        let roots = RootStore::bundled().unwrap();
+        assert!(roots.len() >= 10);
+        for anchor in roots.anchors() {
+            assert!(!anchor.subject().is_empty());
+            assert!(!anchor.public_key().key.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_parse_root_certificate() {
+        let pem = super::super::roots::BUNDLED_ROOTS;
+        let der = super::super::roots::pem_certificates(pem)
+            .next()
+            .unwrap()
+            .unwrap();
+        let cert = Certificate::parse(&der).unwrap();
+        // Self-signed CA
+        assert_eq!(cert.issuer, cert.subject);
+        assert!(cert.is_ca);
+        assert!(cert.key_cert_sign);
+        assert!(cert.not_before < cert.not_after);
+        assert!(!cert.matches_name(&ServerName::Dns("example.com".into())));
+
+        let mut truncated = der.clone();
+        truncated.pop();
+        assert!(Certificate::parse(&truncated).is_err());
+        let mut trailing = der;
+        trailing.push(0);
+        assert!(Certificate::parse(&trailing).is_err());
+    }
+}
@@ -11,13 +11,18 @@
  *   MINIMAL_TEST_PASS
  *   EXEC_TEST_PASS
  *
- * Syscall numbers from kernel/src/syscall/mod.rs:
+ * Syscall numbers from libs/veridian-abi (see <veridian/syscall.h>):
  *   SYS_PROCESS_EXIT = 11  (status)
  *   SYS_PROCESS_FORK = 12  ()
  *   SYS_PROCESS_EXEC = 13  (path_ptr, argv_ptr, envp_ptr)
  *   SYS_PROCESS_WAIT = 14  (pid, status_ptr, options)
  *   SYS_FILE_WRITE   = 53  (fd, buf, count)
  *
+ * Architecture-specific calling conventions:
+ *   x86_64:  syscall   -- nr in rax, args in rdi/rsi/rdx/r10/r8/r9
+ *   aarch64: svc #0    -- nr in x8,  args in x0-x5
+ *   riscv64: ecall     -- nr in a7,  args in a0-a5
+ *
  * Build: ${CC} -nostdlib -nostdinc -static -ffreestanding -o exec_test exec_test.c
  */
 
@@ -66,8 +71,94 @@ static long syscall3(long nr, long a1, long a2, long a3)
     return ret;
 }
 
+#elif defined(__aarch64__)
+
+static long syscall0(long nr)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0");
+    __asm__ volatile (
+        "svc #0"
+        : "=r"(x0)
+        : "r"(x8)
+        : "memory"
+    );
+    return x0;
+}
+
+static long syscall1(long nr, long a1)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    __asm__ volatile (
+        "svc #0"
+        : "+r"(x0)
+        : "r"(x8)
+        : "memory"
+    );
+    return x0;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    __asm__ volatile (
+        "svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2)
+        : "memory"
+    );
+    return x0;
+}
+
+#elif defined(__riscv) && __riscv_xlen == 64
+
+static long syscall0(long nr)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0");
+    __asm__ volatile (
+        "ecall"
+        : "=r"(a0)
+        : "r"(a7)
+        : "memory"
+    );
+    return a0;
+}
+
+static long syscall1(long nr, long a1)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    __asm__ volatile (
+        "ecall"
+        : "+r"(a0)
+        : "r"(a7)
+        : "memory"
+    );
+    return a0;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    register long a7  __asm__("a7") = nr;
+    register long a0  __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    __asm__ volatile (
+        "ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2)
+        : "memory"
+    );
+    return a0;
+}
+
 #else
-#error "exec_test only supports x86_64 for now"
+#error "Unsupported architecture for exec_test"
 #endif
 
 static void write_str(const char *s)
@@ -8,7 +8,7 @@
  * This validates that the syscall ABI is correct and that a
  * freestanding static binary can execute on VeridianOS.
  *
- * Syscall numbers from kernel/src/syscall/mod.rs:
+ * Syscall numbers from libs/veridian-abi (see <veridian/syscall.h>):
  *   SYS_FILE_WRITE   = 53   (fd, buf, count)
  *   SYS_PROCESS_EXIT = 11   (status)
  *
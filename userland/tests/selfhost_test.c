@@ -22,30 +22,14 @@
 
 #include <veridian/syscall.h>
 
-/* Raw syscall: write(fd, buf, len) via VeridianOS SYS_FILE_WRITE (53) */
+/* write(fd, buf, len) via VeridianOS SYS_FILE_WRITE (53) */
 static long do_write(int fd, const void *buf, unsigned long len) {
-    long ret;
-    __asm__ volatile (
-        "syscall"
-        : "=a" (ret)
-        : "0" ((long)SYS_FILE_WRITE),
-          "D" ((long)fd),     /* rdi = fd */
-          "S" ((long)buf),    /* rsi = buf */
-          "d" ((long)len)     /* rdx = len */
-        : "rcx", "r11", "memory"
-    );
-    return ret;
+    return veridian_syscall3(SYS_FILE_WRITE, fd, buf, len);
 }
 
-/* Raw syscall: exit(code) via VeridianOS SYS_PROCESS_EXIT (11) */
+/* exit(code) via VeridianOS SYS_PROCESS_EXIT (11) */
 static void do_exit(int code) {
-    __asm__ volatile (
-        "syscall"
-        :
-        : "a" ((long)SYS_PROCESS_EXIT),
-          "D" ((long)code)
-        : "rcx", "r11", "memory"
-    );
+    veridian_syscall1(SYS_PROCESS_EXIT, code);
     __builtin_unreachable();
 }
 
@@ -0,0 +1,286 @@
+/*
+ * VeridianOS End-to-End Test -- syscall_abi_test.c
+ *
+ * Copyright (c) 2025-2026 VeridianOS Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * Checks the syscall register convention of each architecture using raw
+ * syscalls only -- no libc.  Every argument register is exercised by a
+ * call that fails or misbehaves if the value lands in the wrong register:
+ * the ABI handshake (1 argument), an anonymous mmap (5 arguments; the
+ * flags in arg4 are rejected unless exactly one of MAP_PRIVATE/MAP_SHARED
+ * is set), a write from the mapping (3 arguments) and munmap (2).
+ *
+ * Expected output on success:
+ *   SYSCALL_ABI_TEST_PASS
+ *
+ * Syscall numbers from libs/veridian-abi (see <veridian/syscall.h>):
+ *   SYS_PROCESS_EXIT = 11  (status)
+ *   SYS_MEMORY_MAP   = 20  (addr, len, prot, flags, fd_offset)
+ *   SYS_MEMORY_UNMAP = 21  (addr, len)
+ *   SYS_FILE_WRITE   = 53  (fd, buf, count)
+ *   SYS_ABI_VERSION  = 86  (required_version)
+ *
+ * Architecture-specific calling conventions:
+ *   x86_64:  syscall   -- nr in rax, args in rdi/rsi/rdx/r10/r8/r9
+ *   aarch64: svc #0    -- nr in x8,  args in x0-x5
+ *   riscv64: ecall     -- nr in a7,  args in a0-a5
+ *
+ * Build: ${CC} -nostdlib -nostdinc -static -ffreestanding -o syscall_abi_test syscall_abi_test.c
+ */
+
+#define SYS_PROCESS_EXIT 11
+#define SYS_MEMORY_MAP   20
+#define SYS_MEMORY_UNMAP 21
+#define SYS_FILE_WRITE   53
+#define SYS_ABI_VERSION  86
+#define STDOUT_FD        1
+
+#define PROT_READ     0x1
+#define PROT_WRITE    0x2
+#define MAP_PRIVATE   0x02
+#define MAP_ANONYMOUS 0x20
+
+#define MAP_LEN       8192
+
+/* ========================================================================= */
+/* Architecture-specific raw syscall wrappers                                */
+/* ========================================================================= */
+
+#if defined(__x86_64__)
+
+static long syscall1(long nr, long a1)
+{
+    long ret;
+    __asm__ volatile (
+        "syscall"
+        : "=a"(ret)
+        : "a"(nr), "D"(a1)
+        : "rcx", "r11", "memory"
+    );
+    return ret;
+}
+
+static long syscall2(long nr, long a1, long a2)
+{
+    long ret;
+    __asm__ volatile (
+        "syscall"
+        : "=a"(ret)
+        : "a"(nr), "D"(a1), "S"(a2)
+        : "rcx", "r11", "memory"
+    );
+    return ret;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    long ret;
+    __asm__ volatile (
+        "syscall"
+        : "=a"(ret)
+        : "a"(nr), "D"(a1), "S"(a2), "d"(a3)
+        : "rcx", "r11", "memory"
+    );
+    return ret;
+}
+
+static long syscall5(long nr, long a1, long a2, long a3, long a4, long a5)
+{
+    long ret;
+    register long r10 __asm__("r10") = a4;
+    register long r8  __asm__("r8")  = a5;
+    __asm__ volatile (
+        "syscall"
+        : "=a"(ret)
+        : "a"(nr), "D"(a1), "S"(a2), "d"(a3), "r"(r10), "r"(r8)
+        : "rcx", "r11", "memory"
+    );
+    return ret;
+}
+
+#elif defined(__aarch64__)
+
+static long syscall1(long nr, long a1)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    __asm__ volatile (
+        "svc #0"
+        : "+r"(x0)
+        : "r"(x8)
+        : "memory"
+    );
+    return x0;
+}
+
+static long syscall2(long nr, long a1, long a2)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    __asm__ volatile (
+        "svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1)
+        : "memory"
+    );
+    return x0;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    __asm__ volatile (
+        "svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2)
+        : "memory"
+    );
+    return x0;
+}
+
+static long syscall5(long nr, long a1, long a2, long a3, long a4, long a5)
+{
+    register long x8 __asm__("x8") = nr;
+    register long x0 __asm__("x0") = a1;
+    register long x1 __asm__("x1") = a2;
+    register long x2 __asm__("x2") = a3;
+    register long x3 __asm__("x3") = a4;
+    register long x4 __asm__("x4") = a5;
+    __asm__ volatile (
+        "svc #0"
+        : "+r"(x0)
+        : "r"(x8), "r"(x1), "r"(x2), "r"(x3), "r"(x4)
+        : "memory"
+    );
+    return x0;
+}
+
+#elif defined(__riscv) && __riscv_xlen == 64
+
+static long syscall1(long nr, long a1)
+{
+    register long a7 __asm__("a7") = nr;
+    register long a0 __asm__("a0") = a1;
+    __asm__ volatile (
+        "ecall"
+        : "+r"(a0)
+        : "r"(a7)
+        : "memory"
+    );
+    return a0;
+}
+
+static long syscall2(long nr, long a1, long a2)
+{
+    register long a7  __asm__("a7") = nr;
+    register long a0  __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    __asm__ volatile (
+        "ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1)
+        : "memory"
+    );
+    return a0;
+}
+
+static long syscall3(long nr, long a1, long a2, long a3)
+{
+    register long a7  __asm__("a7") = nr;
+    register long a0  __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    __asm__ volatile (
+        "ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2)
+        : "memory"
+    );
+    return a0;
+}
+
+static long syscall5(long nr, long a1, long a2, long a3, long a4, long a5)
+{
+    register long a7  __asm__("a7") = nr;
+    register long a0  __asm__("a0") = a1;
+    register long ra1 __asm__("a1") = a2;
+    register long ra2 __asm__("a2") = a3;
+    register long ra3 __asm__("a3") = a4;
+    register long ra4 __asm__("a4") = a5;
+    __asm__ volatile (
+        "ecall"
+        : "+r"(a0)
+        : "r"(a7), "r"(ra1), "r"(ra2), "r"(ra3), "r"(ra4)
+        : "memory"
+    );
+    return a0;
+}
+
+#else
+#error "Unsupported architecture for syscall_abi_test"
+#endif
+
+/* ========================================================================= */
+/* Test                                                                      */
+/* ========================================================================= */
+
+static long str_len(const char *s)
+{
+    long len = 0;
+    while (s[len]) len++;
+    return len;
+}
+
+static void write_str(const char *s)
+{
+    syscall3(SYS_FILE_WRITE, STDOUT_FD, (long)s, str_len(s));
+}
+
+static void fail(const char *step)
+{
+    write_str("SYSCALL_ABI_TEST_FAIL: ");
+    write_str(step);
+    write_str("\n");
+    syscall1(SYS_PROCESS_EXIT, 1);
+    __builtin_unreachable();
+}
+
+void _start(void)
+{
+    /* Query only: the kernel returns (major << 16) | minor. */
+    long version = syscall1(SYS_ABI_VERSION, 0);
+    if (version < 0 || (version >> 16) != 1)
+        fail("abi version");
+
+    long addr = syscall5(SYS_MEMORY_MAP, 0, MAP_LEN, PROT_READ | PROT_WRITE,
+                         MAP_PRIVATE | MAP_ANONYMOUS, 0);
+    if (addr < 0)
+        fail("mmap");
+
+    /* Fill both pages, then print the message from the end of the mapping. */
+    static const char msg[] = "SYSCALL_ABI_TEST_PASS\n";
+    volatile char *mem = (volatile char *)addr;
+    for (long i = 0; i < MAP_LEN; i++)
+        mem[i] = (char)i;
+    for (long i = 0; i < MAP_LEN; i++)
+        if (mem[i] != (char)i)
+            fail("mmap contents");
+
+    long len = sizeof(msg) - 1;
+    char *out = (char *)addr + MAP_LEN - len;
+    for (long i = 0; i < len; i++)
+        out[i] = msg[i];
+
+    if (syscall3(SYS_FILE_WRITE, STDOUT_FD, (long)out, len) != len)
+        fail("write");
+    if (syscall2(SYS_MEMORY_UNMAP, addr, MAP_LEN) < 0)
+        fail("munmap");
+
+    syscall1(SYS_PROCESS_EXIT, 0);
+    __builtin_unreachable();
+}
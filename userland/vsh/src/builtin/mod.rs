@@ -10,7 +10,13 @@ extern crate alloc;
 
 use alloc::{format, string::String, vec::Vec};
 
-use crate::{eprintln, error::Result, print, println, syscall, Shell};
+use errno::Errno;
+
+use crate::{
+    eprintln,
+    error::{Result, VshError},
+    print, println, syscall, Shell,
+};
 
 /// Check if a command name is a builtin.
 pub fn is_builtin(name: &str) -> bool {
@@ -140,8 +146,8 @@ fn builtin_cd(shell: &mut Shell, args: &[String]) -> Result<i32> {
     path_buf.push(0);
 
     let ret = syscall::sys_chdir(path_buf.as_ptr());
-    if ret < 0 {
-        eprintln!("vsh: cd: {}: No such file or directory", target);
+    if let Err(errno) = Errno::from_ret(ret) {
+        eprintln!("vsh: cd: {}: {}", target, VshError::Io(errno));
         return Ok(1);
     }
 
@@ -1040,10 +1046,15 @@ fn builtin_trap(_shell: &mut Shell, args: &[String]) -> Result<i32> {
 // ---------------------------------------------------------------------------
 
 fn builtin_let(shell: &mut Shell, args: &[String]) -> Result<i32> {
-    let vars = shell.vars_map();
     let mut result = 0i64;
     for arg in args {
-        result = crate::parser::arithmetic::eval_arithmetic(arg, &vars).unwrap_or(0);
+        result = match crate::exec::eval_arithmetic(shell, arg) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("vsh: let: {}: {}", arg, e);
+                return Ok(1);
+            }
+        };
     }
     // `let` returns 1 if result is 0, 0 otherwise
     Ok(if result == 0 { 1 } else { 0 })
@@ -6,6 +6,10 @@
 use alloc::string::String;
 use core::fmt;
 
+use errno::Errno;
+
+use crate::locale::{tr, write_tr};
+
 /// Central error type for all vsh operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(dead_code)] // All variants are part of the shell error API
@@ -14,8 +18,8 @@ pub enum VshError {
     Syntax(String),
     /// A command was not found.
     CommandNotFound(String),
-    /// An I/O error occurred (with errno).
-    Io(i32),
+    /// A system call failed with this error number.
+    Io(Errno),
     /// A variable or parameter expansion error.
     Expansion(String),
     /// Redirection failed.
@@ -47,25 +51,36 @@ pub enum VshError {
 impl fmt::Display for VshError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            VshError::Syntax(msg) => write!(f, "syntax error: {}", msg),
-            VshError::CommandNotFound(cmd) => write!(f, "{}: command not found", cmd),
-            VshError::Io(errno) => write!(f, "I/O error (errno {})", errno),
-            VshError::Expansion(msg) => write!(f, "expansion error: {}", msg),
-            VshError::Redirection(msg) => write!(f, "redirection error: {}", msg),
-            VshError::Signal(sig) => write!(f, "received signal {}", sig),
-            VshError::PermissionDenied(path) => write!(f, "{}: Permission denied", path),
-            VshError::NotANumber(s) => write!(f, "{}: not a valid number", s),
-            VshError::DivisionByZero => write!(f, "division by zero"),
-            VshError::ReadOnly(name) => write!(f, "{}: readonly variable", name),
-            VshError::InvalidArgument(msg) => write!(f, "invalid argument: {}", msg),
-            VshError::ForkFailed => write!(f, "fork: failed to create child process"),
-            VshError::ExecFailed(cmd) => write!(f, "{}: exec failed", cmd),
-            VshError::PipeFailed => write!(f, "pipe: failed to create pipe"),
-            VshError::OutOfMemory => write!(f, "out of memory"),
+            VshError::Syntax(msg) => write_tr(f, "syntax error: {}", &[msg]),
+            VshError::CommandNotFound(cmd) => write_tr(f, "{}: command not found", &[cmd]),
+            VshError::Io(errno) => f.write_str(tr(errno.message())),
+            VshError::Expansion(msg) => write_tr(f, "expansion error: {}", &[msg]),
+            VshError::Redirection(msg) => write_tr(f, "redirection error: {}", &[msg]),
+            VshError::Signal(sig) => write_tr(f, "received signal {}", &[sig]),
+            VshError::PermissionDenied(path) => write_tr(f, "{}: Permission denied", &[path]),
+            VshError::NotANumber(s) => write_tr(f, "{}: not a valid number", &[s]),
+            VshError::DivisionByZero => f.write_str(tr("division by zero")),
+            VshError::ReadOnly(name) => write_tr(f, "{}: readonly variable", &[name]),
+            VshError::InvalidArgument(msg) => write_tr(f, "invalid argument: {}", &[msg]),
+            VshError::ForkFailed => f.write_str(tr("fork: failed to create child process")),
+            VshError::ExecFailed(cmd) => write_tr(f, "{}: exec failed", &[cmd]),
+            VshError::PipeFailed => f.write_str(tr("pipe: failed to create pipe")),
+            VshError::OutOfMemory => f.write_str(tr("out of memory")),
             VshError::Exit(code) => write!(f, "exit {}", code),
         }
     }
 }
 
+impl From<shell_syntax::error::Error> for VshError {
+    fn from(e: shell_syntax::error::Error) -> Self {
+        use shell_syntax::error::Error;
+        match e {
+            Error::Syntax(msg) => VshError::Syntax(msg),
+            Error::NotANumber(s) => VshError::NotANumber(s),
+            Error::DivisionByZero => VshError::DivisionByZero,
+        }
+    }
+}
+
 /// Result type alias for vsh operations.
 pub type Result<T> = core::result::Result<T, VshError>;
@@ -11,6 +11,7 @@ use alloc::string::String;
 use crate::{
     eprintln,
     error::{Result, VshError},
+    expand::regex::Regex,
     parser::ast::*,
     Shell,
 };
@@ -123,23 +124,20 @@ fn execute_for(shell: &mut Shell, clause: &ForClause) -> Result<i32> {
 
 /// for ((init; cond; step)); do body; done
 fn execute_arith_for(shell: &mut Shell, clause: &ArithForClause) -> Result<i32> {
-    let vars = shell.vars_map();
-
     // Run init expression
-    if !clause.init.is_empty() {
-        let _ = crate::parser::arithmetic::eval_arithmetic(&clause.init, &vars);
+    if !clause.init.is_empty() && eval_arith(shell, &clause.init).is_none() {
+        return Ok(1);
     }
 
     let mut last_status = 0;
 
     loop {
-        // Check condition
+        // Check condition (an empty one is true)
         if !clause.condition.is_empty() {
-            let vars = shell.vars_map();
-            let cond =
-                crate::parser::arithmetic::eval_arithmetic(&clause.condition, &vars).unwrap_or(0);
-            if cond == 0 {
-                break;
+            match eval_arith(shell, &clause.condition) {
+                Some(0) => break,
+                Some(_) => {}
+                None => return Ok(1),
             }
         }
 
@@ -151,15 +149,25 @@ fn execute_arith_for(shell: &mut Shell, clause: &ArithForClause) -> Result<i32>
         }
 
         // Run step expression
-        if !clause.step.is_empty() {
-            let vars = shell.vars_map();
-            let _ = crate::parser::arithmetic::eval_arithmetic(&clause.step, &vars);
+        if !clause.step.is_empty() && eval_arith(shell, &clause.step).is_none() {
+            return Ok(1);
         }
     }
 
     Ok(last_status)
 }
 
+/// Evaluate an arithmetic expression, reporting it if it is invalid.
+fn eval_arith(shell: &mut Shell, expr: &str) -> Option<i64> {
+    match super::eval_arithmetic(shell, expr) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("vsh: {}: {}", expr.trim(), e);
+            None
+        }
+    }
+}
+
 /// case word in pattern) body ;; ... esac
 fn execute_case(shell: &mut Shell, clause: &CaseClause) -> Result<i32> {
     let word_expanded = super::expand_word(shell, &clause.word);
@@ -266,10 +274,11 @@ fn execute_select(shell: &mut Shell, clause: &SelectClause) -> Result<i32> {
 
 /// (( expr )) -- arithmetic
 fn execute_arith_evaluation(shell: &mut Shell, expr: &str) -> Result<i32> {
-    let vars = shell.vars_map();
-    let result = crate::parser::arithmetic::eval_arithmetic(expr, &vars).unwrap_or(0);
-    // Returns 0 if non-zero, 1 if zero (like Bash)
-    Ok(if result != 0 { 0 } else { 1 })
+    // Returns 0 if non-zero, 1 if zero or invalid (like Bash)
+    Ok(match eval_arith(shell, expr) {
+        Some(0) | None => 1,
+        Some(_) => 0,
+    })
 }
 
 /// [[ expr ]] -- conditional expression
@@ -278,67 +287,66 @@ fn execute_conditional(shell: &mut Shell, expr: &ConditionalExpr) -> Result<i32>
     Ok(if result { 0 } else { 1 })
 }
 
+/// Evaluate a `[[ ]]` expression.  Operands are not split or globbed; the
+/// right-hand side of `==`, `!=` and `=~` is a pattern in which quoted
+/// text matches literally.
 fn check_cond_expr(shell: &mut Shell, expr: &ConditionalExpr) -> bool {
     match expr {
         ConditionalExpr::Unary(op, word) => {
-            let expanded = super::expand_word(shell, word);
-            let val = if expanded.is_empty() {
-                String::new()
-            } else {
-                expanded[0].clone()
-            };
+            let val = super::expand_string(shell, word);
             check_unary_test(op, &val)
         }
         ConditionalExpr::Binary(left, op, right) => {
-            let l = {
-                let expanded = super::expand_word(shell, left);
-                if expanded.is_empty() {
-                    String::new()
-                } else {
-                    expanded[0].clone()
+            let l = super::expand_string(shell, left);
+            match op.as_str() {
+                "==" | "=" | "!=" => {
+                    let pattern = super::expand_pattern(shell, right);
+                    crate::expand::glob::pattern_match(&pattern, &l) == (op != "!=")
                 }
-            };
-            let r = {
-                let expanded = super::expand_word(shell, right);
-                if expanded.is_empty() {
-                    String::new()
-                } else {
-                    expanded[0].clone()
+                "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge" => {
+                    // Both operands are arithmetic expressions
+                    let r = super::expand_string(shell, right);
+                    match (eval_arith(shell, &l), eval_arith(shell, &r)) {
+                        (Some(a), Some(b)) => compare_integers(op, a, b),
+                        _ => false,
+                    }
                 }
-            };
-            check_binary_test(op, &l, &r)
+                _ => {
+                    let r = super::expand_string(shell, right);
+                    check_binary_test(op, &l, &r)
+                }
+            }
         }
         ConditionalExpr::Regex(left, right) => {
-            let l = {
-                let expanded = super::expand_word(shell, left);
-                if expanded.is_empty() {
-                    String::new()
-                } else {
-                    expanded[0].clone()
-                }
-            };
-            let r = {
-                let expanded = super::expand_word(shell, right);
-                if expanded.is_empty() {
-                    String::new()
-                } else {
-                    expanded[0].clone()
-                }
-            };
-            // Simplified regex: use glob match
-            crate::expand::glob::glob_match(&r, &l)
+            let text = super::expand_string(shell, left);
+            let pattern = super::expand_pattern(shell, right);
+            match_regex(shell, &pattern, &text)
         }
         ConditionalExpr::Not(inner) => !check_cond_expr(shell, inner),
         ConditionalExpr::And(a, b) => check_cond_expr(shell, a) && check_cond_expr(shell, b),
         ConditionalExpr::Or(a, b) => check_cond_expr(shell, a) || check_cond_expr(shell, b),
         ConditionalExpr::Group(inner) => check_cond_expr(shell, inner),
-        ConditionalExpr::Word(word) => {
-            let expanded = super::expand_word(shell, word);
-            !expanded.is_empty() && !expanded[0].is_empty()
-        }
+        ConditionalExpr::Word(word) => !super::expand_string(shell, word).is_empty(),
     }
 }
 
+/// `text =~ pattern`: on a match, `BASH_REMATCH` holds the matched text
+/// and then each group's; otherwise it is emptied.
+fn match_regex(shell: &mut Shell, pattern: &str, text: &str) -> bool {
+    let captures = match Regex::new(pattern) {
+        Some(regex) => regex.captures(text),
+        None => {
+            eprintln!("vsh: {}: invalid regular expression", pattern);
+            None
+        }
+    };
+    let matched = captures.is_some();
+    shell
+        .env
+        .set_global_array("BASH_REMATCH", captures.unwrap_or_default());
+    matched
+}
+
 fn check_unary_test(op: &str, val: &str) -> bool {
     match op {
         "-z" => val.is_empty(),
@@ -364,22 +372,26 @@ fn check_unary_test(op: &str, val: &str) -> bool {
 
 fn check_binary_test(op: &str, left: &str, right: &str) -> bool {
     match op {
-        "==" | "=" => left == right,
-        "!=" => left != right,
         "<" => left < right,
         ">" => left > right,
-        "-eq" => parse_i64(left).unwrap_or(0) == parse_i64(right).unwrap_or(0),
-        "-ne" => parse_i64(left).unwrap_or(0) != parse_i64(right).unwrap_or(0),
-        "-lt" => parse_i64(left).unwrap_or(0) < parse_i64(right).unwrap_or(0),
-        "-le" => parse_i64(left).unwrap_or(0) <= parse_i64(right).unwrap_or(0),
-        "-gt" => parse_i64(left).unwrap_or(0) > parse_i64(right).unwrap_or(0),
-        "-ge" => parse_i64(left).unwrap_or(0) >= parse_i64(right).unwrap_or(0),
         "-ef" => left == right,
         "-nt" | "-ot" => false,
         _ => false,
     }
 }
 
+fn compare_integers(op: &str, left: i64, right: i64) -> bool {
+    match op {
+        "-eq" => left == right,
+        "-ne" => left != right,
+        "-lt" => left < right,
+        "-le" => left <= right,
+        "-gt" => left > right,
+        "-ge" => left >= right,
+        _ => false,
+    }
+}
+
 fn parse_usize(s: &str) -> Option<usize> {
     let mut n: usize = 0;
     for b in s.bytes() {
@@ -415,29 +427,3 @@ fn parse_i32(s: &str) -> Option<i32> {
     }
     Some(if neg { -n } else { n })
 }
-
-fn parse_i64(s: &str) -> Option<i64> {
-    let s = s.trim();
-    let bytes = s.as_bytes();
-    if bytes.is_empty() {
-        return None;
-    }
-    let mut n: i64 = 0;
-    let mut neg = false;
-    let mut i = 0;
-    if bytes[0] == b'-' {
-        neg = true;
-        i = 1;
-    }
-    if i >= bytes.len() {
-        return None;
-    }
-    while i < bytes.len() {
-        if !bytes[i].is_ascii_digit() {
-            return None;
-        }
-        n = n.checked_mul(10)?.checked_add((bytes[i] - b'0') as i64)?;
-        i += 1;
-    }
-    Some(if neg { -n } else { n })
-}
@@ -6,9 +6,15 @@
 
 extern crate alloc;
 
-use alloc::{string::String, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
 
-use crate::{error::Result, expand, parser::ast::*, println, Shell};
+use crate::{
+    eprintln,
+    error::{Result, VshError},
+    expand,
+    parser::ast::*,
+    println, Shell,
+};
 
 pub mod compound;
 pub mod coproc;
@@ -20,6 +26,7 @@ pub mod script;
 pub mod simple;
 pub mod source;
 pub mod subshell;
+pub mod substitute;
 
 // ---------------------------------------------------------------------------
 // Top-level execution entry points
@@ -81,6 +88,7 @@ pub fn execute_pipeline(shell: &mut Shell, pipe: &Pipeline) -> Result<i32> {
     // Single command: no pipe needed
     if cmds.len() == 1 {
         let status = execute_command(shell, &cmds[0])?;
+        pipeline::set_pipestatus(shell, &[status]);
         return Ok(if pipe.negated {
             if status == 0 {
                 1
@@ -106,7 +114,17 @@ pub fn execute_pipeline(shell: &mut Shell, pipe: &Pipeline) -> Result<i32> {
 }
 
 /// Execute a single command (simple, compound, function def, or coproc).
+///
+/// Process substitutions started while expanding the command are closed
+/// and reaped once it has finished.
 pub fn execute_command(shell: &mut Shell, cmd: &Command) -> Result<i32> {
+    let mark = shell.proc_subs.len();
+    let result = execute_command_inner(shell, cmd);
+    substitute::finish_proc_subs(shell, mark);
+    result
+}
+
+fn execute_command_inner(shell: &mut Shell, cmd: &Command) -> Result<i32> {
     match cmd {
         Command::Simple(simple) => simple::execute_simple(shell, simple),
         Command::Compound(compound, redirects) => {
@@ -148,18 +166,104 @@ fn execute_background(shell: &mut Shell, list: &AndOrList) -> Result<i32> {
 // Word expansion helpers (used by submodules)
 // ---------------------------------------------------------------------------
 
+/// List the names in the directory `path` for pathname expansion, or
+/// `None` if it cannot be opened.
+pub fn read_directory(path: &str) -> Option<Vec<String>> {
+    let mut path_buf = Vec::with_capacity(path.len() + 1);
+    path_buf.extend_from_slice(path.as_bytes());
+    path_buf.push(0);
+
+    let fd = crate::syscall::sys_open(path_buf.as_ptr(), crate::syscall::O_RDONLY, 0);
+    if fd < 0 {
+        return None;
+    }
+    let fd = fd as i32;
+
+    let mut names = Vec::new();
+    let mut buf = [0u8; 2048];
+    loop {
+        let n = crate::syscall::sys_getdents64(fd, &mut buf);
+        if n <= 0 {
+            break;
+        }
+        // d_ino (8), d_off (8), d_reclen (2), d_type (1), NUL-terminated name
+        let mut pos = 0;
+        while pos + 19 < n as usize {
+            let reclen = u16::from_ne_bytes([buf[pos + 16], buf[pos + 17]]) as usize;
+            if reclen == 0 {
+                break;
+            }
+            let name = &buf[pos + 19..(pos + reclen).min(n as usize)];
+            let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            names.push(String::from_utf8_lossy(&name[..len]).into_owned());
+            pos += reclen;
+        }
+    }
+    crate::syscall::sys_close(fd);
+    Some(names)
+}
+
 /// Expand a single AST Word using the shell's current state.
-pub fn expand_word(shell: &Shell, word: &Word) -> Vec<String> {
+pub fn expand_word(shell: &mut Shell, word: &Word) -> Vec<String> {
     let vars = shell.vars_map();
     let special = shell.special_vars();
     let do_glob = !shell.config.set_opts.noglob;
-    expand::expand_word(&word.raw, &vars, &special, do_glob)
+    expand::expand_word(&word.raw, &vars, &special, do_glob, shell)
 }
 
 /// Expand a list of AST Words.
-pub fn expand_words(shell: &Shell, words: &[Word]) -> Vec<String> {
+pub fn expand_words(shell: &mut Shell, words: &[Word]) -> Vec<String> {
     let vars = shell.vars_map();
     let special = shell.special_vars();
     let do_glob = !shell.config.set_opts.noglob;
-    expand::expand_words(words, &vars, &special, do_glob)
+    expand::expand_words(words, &vars, &special, do_glob, shell)
+}
+
+/// Expand a word to a single string (no word splitting).
+pub fn expand_string(shell: &mut Shell, word: &Word) -> String {
+    let vars = shell.vars_map();
+    let special = shell.special_vars();
+    expand::expand_string(&word.raw, &vars, &special, shell)
+}
+
+/// Expand a `[[ ]]` pattern operand, keeping quoted text literal.
+pub fn expand_pattern(shell: &mut Shell, word: &Word) -> String {
+    let vars = shell.vars_map();
+    let special = shell.special_vars();
+    expand::expand_pattern(&word.raw, &vars, &special, shell)
+}
+
+/// Expand the body of an unquoted here-document.
+pub fn expand_heredoc(shell: &mut Shell, body: &str) -> String {
+    let vars = shell.vars_map();
+    let special = shell.special_vars();
+    expand::expand_heredoc(body, &vars, &special, shell)
+}
+
+/// Evaluate an arithmetic expression, assigning the variables it sets.
+pub fn eval_arithmetic(shell: &mut Shell, expr: &str) -> Result<i64> {
+    let vars = shell.vars_map();
+    let value = crate::parser::arithmetic::eval_arithmetic(expr, &vars, &mut |name, value| {
+        assign_variable(shell, name, &format!("{}", value))
+    })?;
+    Ok(value)
+}
+
+/// Assign `value` to `name`, or to an element of an indexed array when
+/// `name` is `array[index]`.
+pub fn assign_variable(shell: &mut Shell, name: &str, value: &str) {
+    let element = name.strip_suffix(']').and_then(|n| n.split_once('['));
+    let result = match element {
+        Some((array, index)) => match index.parse::<usize>() {
+            Ok(index) => shell.env.set_array_element(array, index, value),
+            Err(_) => Err(VshError::InvalidArgument(format!(
+                "{}: bad array subscript",
+                name
+            ))),
+        },
+        None => shell.env.set(name, value),
+    };
+    if let Err(e) = result {
+        eprintln!("vsh: {}", e);
+    }
 }
@@ -3,12 +3,20 @@
 //! Connects multiple commands with pipes: `cmd1 | cmd2 | cmd3`.
 //! Each command in the pipeline runs in its own forked process (except
 //! optionally the last command with `lastpipe` shopt).
+//!
+//! Stage `i` gets the read end of pipe `i - 1` as stdin and the write end
+//! of pipe `i` as stdout (and stderr too for `|&`).  The parent closes each
+//! pipe end as soon as the children that need it have been forked, so a
+//! reader sees EOF once every writer before it has exited.  Builtins,
+//! functions and compound commands run in the forked child like a
+//! subshell; a simple external command replaces the child with `execve`.
 
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{format, string::String, vec::Vec};
 
 use crate::{
+    eprintln,
     error::{Result, VshError},
     parser::ast::Command,
     syscall, Shell,
@@ -18,121 +26,136 @@ use crate::{
 ///
 /// Creates pipes between adjacent commands, forks processes for each
 /// command, and waits for all to complete.  Returns the exit status of
-/// the last command in the pipeline.
+/// the last command in the pipeline (or, with `pipefail`, of the rightmost
+/// failing one) and records every stage's status in `PIPESTATUS`.
 pub fn execute_pipeline_multi(
     shell: &mut Shell,
     commands: &[Command],
-    _pipe_stderr: bool,
+    pipe_stderr: bool,
 ) -> Result<i32> {
     let n = commands.len();
     if n == 0 {
         return Ok(0);
     }
     if n == 1 {
-        return super::execute_command(shell, &commands[0]);
+        let status = super::execute_command(shell, &commands[0])?;
+        set_pipestatus(shell, &[status]);
+        return Ok(status);
     }
 
-    // Create n-1 pipes
-    let mut pipes: Vec<[i32; 2]> = Vec::with_capacity(n - 1);
-    for _ in 0..n - 1 {
-        let mut pipefd = [0i32; 2];
-        let ret = syscall::sys_pipe(&mut pipefd);
-        if ret < 0 {
-            // Close any pipes we already created
-            for p in &pipes {
-                syscall::sys_close(p[0]);
-                syscall::sys_close(p[1]);
-            }
+    let mut child_pids: Vec<i32> = Vec::with_capacity(n);
+    // Read end of the previous stage's pipe (stdin of the next stage)
+    let mut prev_read: Option<i32> = None;
+
+    for (i, cmd) in commands.iter().enumerate() {
+        let last = i == n - 1;
+
+        let mut pipefd = [-1i32; 2];
+        if !last && syscall::sys_pipe(&mut pipefd) < 0 {
+            abort_pipeline(&child_pids, prev_read);
             return Err(VshError::PipeFailed);
         }
-        pipes.push(pipefd);
-    }
-
-    // Fork children for each command
-    let mut child_pids: Vec<i32> = Vec::with_capacity(n);
 
-    for i in 0..n {
         let pid = syscall::sys_fork();
         if pid < 0 {
-            // Fork failed -- kill any children we already started
-            for &cpid in &child_pids {
-                let _ = unsafe { syscall::syscall2(syscall::SYS_PROCESS_KILL, cpid as usize, 9) };
-            }
-            for p in &pipes {
-                syscall::sys_close(p[0]);
-                syscall::sys_close(p[1]);
+            if !last {
+                syscall::sys_close(pipefd[0]);
+                syscall::sys_close(pipefd[1]);
             }
+            abort_pipeline(&child_pids, prev_read);
             return Err(VshError::ForkFailed);
         }
 
         if pid == 0 {
-            // Child process
-
-            // Set up stdin from previous pipe
-            if i > 0 {
-                syscall::sys_dup2(pipes[i - 1][0], 0);
+            // Child: wire stdin/stdout, then run the stage
+            if let Some(fd) = prev_read {
+                syscall::sys_dup2(fd, 0);
+                syscall::sys_close(fd);
             }
-
-            // Set up stdout to next pipe
-            if i < n - 1 {
-                syscall::sys_dup2(pipes[i][1], 1);
+            if !last {
+                syscall::sys_dup2(pipefd[1], 1);
+                if pipe_stderr {
+                    syscall::sys_dup2(pipefd[1], 2);
+                }
+                syscall::sys_close(pipefd[0]);
+                syscall::sys_close(pipefd[1]);
             }
-
-            // Close all pipe fds (they've been dup'd where needed)
-            for p in &pipes {
-                syscall::sys_close(p[0]);
-                syscall::sys_close(p[1]);
-            }
-
-            // Execute the command
-            let status = super::execute_command(shell, &commands[i]).unwrap_or(1);
-            syscall::sys_exit(status);
+            run_stage(shell, cmd);
         }
 
         child_pids.push(pid as i32);
-    }
-
-    // Parent: close all pipe fds
-    for p in &pipes {
-        syscall::sys_close(p[0]);
-        syscall::sys_close(p[1]);
-    }
 
-    // Wait for all children
-    let mut last_status = 0;
-    let mut pipe_statuses: Vec<i32> = Vec::with_capacity(n);
-
-    for &cpid in &child_pids {
-        let (ret, status) = syscall::sys_waitpid(cpid, 0);
-        let exit_code = if ret > 0 {
-            if status & 0x7f == 0 {
-                (status >> 8) & 0xff
-            } else {
-                128 + (status & 0x7f)
-            }
+        // Parent: the children now hold these ends
+        if let Some(fd) = prev_read {
+            syscall::sys_close(fd);
+        }
+        if last {
+            prev_read = None;
         } else {
-            127
-        };
-        pipe_statuses.push(exit_code);
+            syscall::sys_close(pipefd[1]);
+            prev_read = Some(pipefd[0]);
+        }
     }
 
+    // Wait for all children, in pipeline order
+    let pipe_statuses: Vec<i32> = child_pids.iter().map(|&cpid| wait_status(cpid)).collect();
+    set_pipestatus(shell, &pipe_statuses);
+
     // The exit status of a pipeline is the exit status of the last command
-    if let Some(&s) = pipe_statuses.last() {
-        last_status = s;
-    }
+    let mut last_status = pipe_statuses[n - 1];
 
     // If pipefail is set, return the rightmost non-zero exit status
     if shell.config.set_opts.pipefail {
-        for &s in pipe_statuses.iter().rev() {
-            if s != 0 {
-                last_status = s;
-                break;
-            }
+        if let Some(&s) = pipe_statuses.iter().rev().find(|&&s| s != 0) {
+            last_status = s;
         }
     }
 
-    // Store PIPESTATUS array
-    // (In a full implementation, set PIPESTATUS array variable)
-
     Ok(last_status)
 }
+
+/// Run one pipeline stage in a forked child and exit with its status.
+fn run_stage(shell: &mut Shell, cmd: &Command) -> ! {
+    // A lone external command needs no extra fork: exec it in this child
+    shell.exec_in_place = matches!(cmd, Command::Simple(_));
+
+    let status = match super::execute_command(shell, cmd) {
+        Ok(status) => status,
+        Err(VshError::Exit(code)) => code,
+        Err(e) => {
+            eprintln!("vsh: {}", e);
+            1
+        }
+    };
+    syscall::sys_exit(status);
+}
+
+/// Wait for `pid` and decode its exit status the way `$?` reports it.
+pub fn wait_status(pid: i32) -> i32 {
+    let (ret, status) = syscall::sys_waitpid(pid, 0);
+    if ret < 0 {
+        127
+    } else if status & 0x7f == 0 {
+        (status >> 8) & 0xff
+    } else {
+        128 + (status & 0x7f)
+    }
+}
+
+/// Kill and reap the stages already started when the pipeline cannot be
+/// completed, and close the pipe end the next stage would have used.
+fn abort_pipeline(child_pids: &[i32], prev_read: Option<i32>) {
+    if let Some(fd) = prev_read {
+        syscall::sys_close(fd);
+    }
+    for &cpid in child_pids {
+        let _ = unsafe { syscall::syscall2(syscall::SYS_PROCESS_KILL, cpid as usize, 9) };
+        let _ = syscall::sys_waitpid(cpid, 0);
+    }
+}
+
+/// Record the exit status of every stage of the last foreground pipeline.
+pub fn set_pipestatus(shell: &mut Shell, statuses: &[i32]) {
+    let values: Vec<String> = statuses.iter().map(|s| format!("{}", s)).collect();
+    shell.env.set_global_array("PIPESTATUS", values);
+}
@@ -164,7 +164,8 @@ fn apply_redirect(shell: &mut Shell, redir: &Redirect, saved: &mut Vec<SavedFd>)
             // << DELIM or <<- DELIM
             let fd = redir.fd.unwrap_or(0);
             let body = match &redir.target {
-                RedirectTarget::HereDocBody(s) => s.clone(),
+                RedirectTarget::HereDocBody(s, true) => s.clone(),
+                RedirectTarget::HereDocBody(s, false) => super::expand_heredoc(shell, s),
                 _ => String::new(),
             };
 
@@ -6,7 +6,13 @@ extern crate alloc;
 
 use alloc::{string::String, vec::Vec};
 
-use crate::{eprintln, error::Result, syscall, Shell};
+use errno::Errno;
+
+use crate::{
+    eprintln,
+    error::{Result, VshError},
+    syscall, Shell,
+};
 
 /// Read and execute a script file.
 pub fn run_script_file(shell: &mut Shell, path: &str) -> Result<i32> {
@@ -15,11 +21,13 @@ pub fn run_script_file(shell: &mut Shell, path: &str) -> Result<i32> {
     path_buf.extend_from_slice(path.as_bytes());
     path_buf.push(0);
 
-    let fd = syscall::sys_open(path_buf.as_ptr(), syscall::O_RDONLY, 0);
-    if fd < 0 {
-        eprintln!("vsh: {}: No such file or directory", path);
-        return Ok(127);
-    }
+    let fd = match Errno::from_ret(syscall::sys_open(path_buf.as_ptr(), syscall::O_RDONLY, 0)) {
+        Ok(fd) => fd,
+        Err(errno) => {
+            eprintln!("vsh: {}: {}", path, VshError::Io(errno));
+            return Ok(127);
+        }
+    };
 
     // Read the file content
     let mut content = Vec::new();
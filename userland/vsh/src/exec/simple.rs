@@ -17,19 +17,20 @@ use crate::{
 
 /// Execute a simple command.
 pub fn execute_simple(shell: &mut Shell, cmd: &SimpleCommand) -> Result<i32> {
+    // Only this command may replace the process, not ones it runs in turn
+    let exec_in_place = core::mem::take(&mut shell.exec_in_place);
+
     // Expand words
     let expanded_words = super::expand_words(shell, &cmd.words);
 
     // Handle empty command (assignments only)
     if expanded_words.is_empty() {
+        // Its status is that of the last command substitution, if any
+        shell.last_subst_status = None;
+
         // Apply variable assignments to the current environment
         for assign in &cmd.assignments {
-            let value_parts = super::expand_word(shell, &assign.value);
-            let value = if value_parts.is_empty() {
-                String::new()
-            } else {
-                value_parts.join(" ")
-            };
+            let value = super::expand_string(shell, &assign.value);
 
             if assign.append {
                 let old = String::from(shell.env.get_str(&assign.name));
@@ -40,7 +41,7 @@ pub fn execute_simple(shell: &mut Shell, cmd: &SimpleCommand) -> Result<i32> {
                 let _ = shell.env.set(&assign.name, &value);
             }
         }
-        return Ok(0);
+        return Ok(shell.last_subst_status.take().unwrap_or(0));
     }
 
     let cmd_name = &expanded_words[0];
@@ -87,8 +88,8 @@ pub fn execute_simple(shell: &mut Shell, cmd: &SimpleCommand) -> Result<i32> {
         return result;
     }
 
-    // External command: fork/exec
-    let result = execute_external(shell, cmd_name, args, &cmd.assignments);
+    // External command: fork/exec (or just exec in a pipeline stage)
+    let result = execute_external(shell, cmd_name, args, &cmd.assignments, exec_in_place);
     redirect::restore_redirects(&saved);
     result
 }
@@ -104,8 +105,7 @@ fn execute_builtin_with_env(
     let mut saved_vals: Vec<(String, Option<String>)> = Vec::new();
 
     for assign in assignments {
-        let value_parts = super::expand_word(shell, &assign.value);
-        let value = value_parts.join(" ");
+        let value = super::expand_string(shell, &assign.value);
         let old = if shell.env.is_set(&assign.name) {
             Some(String::from(shell.env.get_str(&assign.name)))
         } else {
@@ -128,11 +128,16 @@ fn execute_builtin_with_env(
 }
 
 /// Execute an external command via fork/exec.
+///
+/// With `exec_in_place` (a forked pipeline stage) the command replaces the
+/// current process instead, and this only returns if the command could not
+/// be found.
 fn execute_external(
     shell: &mut Shell,
     cmd: &str,
     args: &[String],
     assignments: &[crate::parser::ast::Assignment],
+    exec_in_place: bool,
 ) -> Result<i32> {
     // Resolve the command path
     let path = match builtin::find_in_path(cmd, shell.env.get_str("PATH")) {
@@ -167,8 +172,7 @@ fn execute_external(
     // Build envp with prefix assignments
     let mut env_strings = shell.env.collect_env();
     for assign in assignments {
-        let value_parts = super::expand_word(shell, &assign.value);
-        let value = value_parts.join(" ");
+        let value = super::expand_string(shell, &assign.value);
         env_strings.push(alloc::format!("{}={}", assign.name, value));
     }
 
@@ -184,7 +188,11 @@ fn execute_external(
     envp_with_null.push(core::ptr::null());
 
     // Fork
-    let pid = syscall::sys_fork();
+    let pid = if exec_in_place {
+        0
+    } else {
+        syscall::sys_fork()
+    };
     if pid < 0 {
         return Err(VshError::ForkFailed);
     }
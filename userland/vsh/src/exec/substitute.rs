@@ -0,0 +1,145 @@
+//! Command and process substitution.
+//!
+//! `$(cmd)` and `` `cmd` `` run `cmd` in a forked subshell with its stdout
+//! connected to a pipe; the shell reads the pipe to EOF and the output,
+//! minus trailing newlines, replaces the substitution.
+//!
+//! `<(cmd)` and `>(cmd)` start `cmd` in a forked subshell connected to a
+//! pipe and expand to `/dev/fd/N`, naming the shell's end of the pipe.
+//! The shell keeps that end open until the command using the path has
+//! finished, then closes it and reaps the subshell.
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{eprintln, error::VshError, expand::Executor, syscall, Shell};
+
+/// A running process substitution.
+#[derive(Debug)]
+pub struct ProcSub {
+    /// The shell's end of the pipe, named by `/dev/fd/N`.
+    pub fd: i32,
+    /// The subshell running the command.
+    pub pid: i32,
+}
+
+impl Executor for Shell {
+    fn command(&mut self, cmd: &str) -> String {
+        let mut pipefd = [-1i32; 2];
+        if syscall::sys_pipe(&mut pipefd) < 0 {
+            eprintln!("vsh: {}", VshError::PipeFailed);
+            return String::new();
+        }
+
+        let pid = syscall::sys_fork();
+        if pid < 0 {
+            syscall::sys_close(pipefd[0]);
+            syscall::sys_close(pipefd[1]);
+            eprintln!("vsh: {}", VshError::ForkFailed);
+            return String::new();
+        }
+        if pid == 0 {
+            syscall::sys_close(pipefd[0]);
+            syscall::sys_dup2(pipefd[1], 1);
+            syscall::sys_close(pipefd[1]);
+            run_subshell(self, cmd);
+        }
+
+        syscall::sys_close(pipefd[1]);
+        let mut output = Vec::new();
+        let mut buf = [0u8; 512];
+        loop {
+            let n = syscall::sys_read(pipefd[0], &mut buf);
+            if n <= 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..n as usize]);
+        }
+        syscall::sys_close(pipefd[0]);
+
+        let status = super::pipeline::wait_status(pid as i32);
+        self.env.last_status = status;
+        self.last_subst_status = Some(status);
+
+        let mut text = String::from_utf8_lossy(&output).into_owned();
+        while text.ends_with('\n') {
+            text.pop();
+        }
+        text
+    }
+
+    fn process(&mut self, cmd: &str, output: bool) -> String {
+        let mut pipefd = [-1i32; 2];
+        if syscall::sys_pipe(&mut pipefd) < 0 {
+            eprintln!("vsh: {}", VshError::PipeFailed);
+            return String::new();
+        }
+        // The subshell reads from `>(cmd)` and writes to `<(cmd)`
+        let (child_end, child_fd, shell_end) = if output {
+            (pipefd[0], 0, pipefd[1])
+        } else {
+            (pipefd[1], 1, pipefd[0])
+        };
+
+        let pid = syscall::sys_fork();
+        if pid < 0 {
+            syscall::sys_close(pipefd[0]);
+            syscall::sys_close(pipefd[1]);
+            eprintln!("vsh: {}", VshError::ForkFailed);
+            return String::new();
+        }
+        if pid == 0 {
+            syscall::sys_close(shell_end);
+            syscall::sys_dup2(child_end, child_fd);
+            syscall::sys_close(child_end);
+            run_subshell(self, cmd);
+        }
+
+        syscall::sys_close(child_end);
+        self.proc_subs.push(ProcSub {
+            fd: shell_end,
+            pid: pid as i32,
+        });
+        format!("/dev/fd/{}", shell_end)
+    }
+
+    fn assign(&mut self, name: &str, value: &str) {
+        super::assign_variable(self, name, value);
+    }
+
+    fn read_dir(&mut self, path: &str) -> Option<Vec<String>> {
+        super::read_directory(path)
+    }
+
+    fn error(&mut self, msg: &str) {
+        eprintln!("vsh: {}", msg);
+    }
+}
+
+/// Close and reap the process substitutions started after the first
+/// `mark` ones.
+pub fn finish_proc_subs(shell: &mut Shell, mark: usize) {
+    if shell.proc_subs.len() <= mark {
+        return;
+    }
+    for sub in shell.proc_subs.split_off(mark) {
+        syscall::sys_close(sub.fd);
+        let _ = syscall::sys_waitpid(sub.pid, 0);
+    }
+}
+
+/// Run `cmd` in a forked subshell and exit with its status.
+fn run_subshell(shell: &mut Shell, cmd: &str) -> ! {
+    shell.interactive = false;
+    shell.proc_subs.clear();
+    let status = match super::eval::eval_string(shell, cmd) {
+        Ok(status) => status,
+        Err(VshError::Exit(code)) => code,
+        Err(e) => {
+            eprintln!("vsh: {}", e);
+            1
+        }
+    };
+    syscall::sys_exit(status);
+}
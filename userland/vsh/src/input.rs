@@ -1,6 +1,7 @@
 //! Raw terminal input handling.
 //!
-//! Provides byte-by-byte reading from fd 0 (stdin) using the read syscall.
+//! Provides byte-by-byte reading from fd 0 (stdin) using the read syscall,
+//! and waiting for input with poll() so an idle prompt sleeps in the kernel.
 //! Handles line editing basics: backspace, arrow keys, ctrl-C, ctrl-D.
 
 use alloc::{string::String, vec::Vec};
@@ -22,6 +23,34 @@ pub fn read_byte() -> Option<u8> {
     }
 }
 
+/// How long an idle prompt sleeps before checking on jobs and the terminal
+/// size again. SIGCHLD and SIGWINCH end the sleep early.
+pub const IDLE_POLL_MS: i32 = 1000;
+
+/// Wait up to `timeout_ms` for input on stdin.
+///
+/// Returns `false` if the wait ended without input (timeout or signal).
+/// If stdin cannot be polled, returns `true` and the next read blocks.
+pub fn wait_for_input(timeout_ms: i32) -> bool {
+    let mut fds = [syscall::PollFd {
+        fd: 0,
+        events: syscall::POLLIN,
+        revents: 0,
+    }];
+    let n = syscall::sys_poll(&mut fds, timeout_ms);
+    n != 0 && n != -syscall::EINTR
+}
+
+/// Size of the terminal on stdin as `(rows, columns)`, or `None` if stdin
+/// is not a terminal.
+pub fn window_size() -> Option<(u16, u16)> {
+    let mut ws = syscall::Winsize::default();
+    if syscall::sys_get_winsize(0, &mut ws) < 0 || ws.cols == 0 {
+        return None;
+    }
+    Some((ws.rows, ws.cols))
+}
+
 /// Read a complete line from stdin with basic line editing support.
 ///
 /// Supports:
@@ -0,0 +1,119 @@
+//! Message translation.
+//!
+//! Error messages are looked up in the gettext catalog for the language
+//! named by `LC_ALL`, `LC_MESSAGES` or `LANG` (the first one set), read
+//! from `/usr/share/locale/<language>/LC_MESSAGES/vsh.mo` or its `.po`
+//! source. The shell re-checks those variables after every command, so
+//! `export LANG=de` takes effect at the next prompt.
+
+extern crate alloc;
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{
+    fmt, ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use i18n::Catalog;
+
+use crate::{syscall, var::ShellEnv};
+
+/// Message domain of the shell.
+const DOMAIN: &str = "vsh";
+
+/// The language a catalog was looked up for, and the catalog if one was
+/// found.
+struct Locale {
+    language: String,
+    catalog: Option<Catalog>,
+}
+
+/// The active locale; null until the first [`update`]. Locales are leaked
+/// rather than freed, so a pointer read here stays valid.
+static LOCALE: AtomicPtr<Locale> = AtomicPtr::new(ptr::null_mut());
+
+fn current() -> Option<&'static Locale> {
+    // SAFETY: LOCALE only ever holds null or a pointer from Box::leak,
+    // which is never freed.
+    unsafe { LOCALE.load(Ordering::Acquire).as_ref() }
+}
+
+/// The language the environment asks for.
+fn requested_language(env: &ShellEnv) -> &str {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .map(|name| env.get_str(name))
+        .find(|value| !value.is_empty())
+        .unwrap_or("")
+}
+
+/// Read a whole file, or `None` if it cannot be opened.
+fn read_file(path: &str) -> Option<Vec<u8>> {
+    let mut path_buf = Vec::with_capacity(path.len() + 1);
+    path_buf.extend_from_slice(path.as_bytes());
+    path_buf.push(0);
+
+    let fd = syscall::sys_open(path_buf.as_ptr(), syscall::O_RDONLY, 0);
+    if fd < 0 {
+        return None;
+    }
+    let mut content = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = syscall::sys_read(fd as i32, &mut buf);
+        if n <= 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..n as usize]);
+    }
+    syscall::sys_close(fd as i32);
+    Some(content)
+}
+
+/// Load the first readable catalog for `language`.
+fn load_catalog(language: &str) -> Option<Catalog> {
+    if !i18n::is_valid_language(language) {
+        return None;
+    }
+    i18n::catalog_paths(language, DOMAIN)
+        .iter()
+        .find_map(|path| {
+            let data = read_file(path)?;
+            if path.ends_with(".po") {
+                Catalog::from_po(core::str::from_utf8(&data).ok()?).ok()
+            } else {
+                Catalog::from_mo(&data).ok()
+            }
+        })
+}
+
+/// Load the catalog for the language in `env` if it changed since the last
+/// call.
+pub fn update(env: &ShellEnv) {
+    let language = requested_language(env);
+    if current().is_some_and(|locale| locale.language == language) {
+        return;
+    }
+    let locale = Box::new(Locale {
+        language: String::from(language),
+        catalog: load_catalog(language),
+    });
+    LOCALE.store(Box::leak(locale), Ordering::Release);
+}
+
+/// The translation of `msgid`.
+pub fn tr(msgid: &str) -> &str {
+    match current().and_then(|locale| locale.catalog.as_ref()) {
+        Some(catalog) => catalog.gettext(msgid),
+        None => msgid,
+    }
+}
+
+/// Write the translation of `template` with `args` substituted.
+pub fn write_tr(
+    f: &mut fmt::Formatter<'_>,
+    template: &str,
+    args: &[&dyn fmt::Display],
+) -> fmt::Result {
+    f.write_str(&i18n::fill(tr(template), args))
+}
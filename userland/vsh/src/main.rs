@@ -12,44 +12,84 @@ mod builtin;
 mod config;
 mod error;
 mod exec;
-mod expand;
 mod input;
 mod jobs;
-mod lexer;
+mod locale;
 mod output;
-mod parser;
 mod prompt;
 mod readline;
+mod startup;
 mod syscall;
 mod var;
 
-use alloc::{collections::BTreeMap, string::String};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
 use core::{
     alloc::{GlobalAlloc, Layout},
     sync::atomic::{AtomicUsize, Ordering},
 };
 
 use config::ShellConfig;
+use errno::Errno;
 use error::VshError;
 use expand::parameter::SpecialVars;
 use jobs::JobTable;
 use prompt::PromptContext;
 use readline::Readline;
+use shell_syntax::{expand, lexer, parser};
 use var::ShellEnv;
+use veridian_abi::heap_profile::{self, HeapStats};
 
 // ============================================================================
 // Global allocator (mmap-based)
 // ============================================================================
 
 /// A simple bump allocator backed by anonymous mmap pages.
+///
+/// With `MALLOC_PROFILE` set it counts allocations and frees in `stats`
+/// for `/proc/<pid>/heap`. Freed memory is never reused, so whatever the
+/// live bytes fall short of the mapped arenas is lost to the bump.
 struct MmapAllocator {
     base: AtomicUsize,
     offset: AtomicUsize,
     capacity: AtomicUsize,
+    stats: HeapStats,
 }
 
 unsafe impl GlobalAlloc for MmapAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller's contract is passed on.
+        let ptr = unsafe { self.bump(layout) };
+        if !ptr.is_null() && self.stats.enabled() {
+            self.stats.count_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, layout: Layout) {
+        // Bump allocator: no individual deallocation.
+        // Memory is reclaimed when the process exits.
+        if self.stats.enabled() {
+            self.stats.count_free(layout.size());
+        }
+    }
+}
+
+impl MmapAllocator {
+    /// Start counting if `MALLOC_PROFILE` (`value`) asks for it. Only counts
+    /// are kept: Rust allocations carry no return address.
+    fn start_profile(&'static self, value: &[u8]) {
+        if heap_profile::parse_mode(value).is_some() {
+            self.stats.enable(heap_profile::MODE_COUNTS);
+            syscall::sys_heap_profile(&self.stats);
+        }
+    }
+
+    /// Carve `layout` out of the current arena, mapping a new one when it
+    /// does not fit.
+    ///
+    /// # Safety
+    /// As for [`GlobalAlloc::alloc`].
+    unsafe fn bump(&self, layout: Layout) -> *mut u8 {
         let size = layout.size();
         let align = layout.align();
 
@@ -105,11 +145,6 @@ unsafe impl GlobalAlloc for MmapAllocator {
             // CAS failed -- retry
         }
     }
-
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
-        // Bump allocator: no individual deallocation.
-        // Memory is reclaimed when the process exits.
-    }
 }
 
 #[global_allocator]
@@ -117,6 +152,7 @@ static ALLOCATOR: MmapAllocator = MmapAllocator {
     base: AtomicUsize::new(0),
     offset: AtomicUsize::new(0),
     capacity: AtomicUsize::new(0),
+    stats: HeapStats::new(),
 };
 
 // ============================================================================
@@ -152,17 +188,33 @@ pub struct Shell {
     pub interactive: bool,
     /// Whether the shell should keep running.
     pub running: bool,
+    /// Set in a forked pipeline stage: the next simple command, if external,
+    /// replaces the process instead of forking again.
+    pub exec_in_place: bool,
+    /// Process substitutions whose pipes are still open (see
+    /// [`exec::substitute`]).
+    pub proc_subs: alloc::vec::Vec<exec::substitute::ProcSub>,
+    /// Exit status of the last command substitution, for the status of a
+    /// command consisting only of assignments.
+    pub last_subst_status: Option<i32>,
 }
 
 impl Shell {
-    /// Create a new interactive shell.
-    fn new_interactive() -> Self {
+    /// Create a new interactive shell with the variables of `environ`
+    /// (`NAME=VALUE` strings) exported.
+    fn new_interactive(environ: &[String]) -> Self {
         let pid = syscall::sys_getpid();
         let mut env = ShellEnv::new();
         env.shell_pid = pid;
 
+        for entry in environ {
+            env.import_env(entry);
+        }
+
         // Set up default environment
-        let _ = env.set_global("SHELL", "/bin/vsh");
+        if !env.is_set("SHELL") {
+            let _ = env.set_global("SHELL", "/bin/vsh");
+        }
 
         // Read current working directory
         let mut cwd_buf = [0u8; 512];
@@ -184,6 +236,9 @@ impl Shell {
             readline: Readline::new(),
             interactive: true,
             running: true,
+            exec_in_place: false,
+            proc_subs: alloc::vec::Vec::new(),
+            last_subst_status: None,
         }
     }
 
@@ -225,9 +280,20 @@ impl Shell {
     }
 
     /// Collect variables as a flat BTreeMap for the expansion engine.
+    ///
+    /// Indexed arrays also appear element by element as `name[i]`, and
+    /// joined as `name[@]` / `name[*]`.
     pub fn vars_map(&self) -> BTreeMap<String, String> {
         let mut map = BTreeMap::new();
         for name in self.env.all_var_names() {
+            if let Some(values) = self.env.get_array_all(&name) {
+                for (i, value) in values.iter().enumerate() {
+                    map.insert(alloc::format!("{}[{}]", name, i), value.clone());
+                }
+                let joined = values.join(" ");
+                map.insert(alloc::format!("{}[*]", name), joined.clone());
+                map.insert(alloc::format!("{}[@]", name), joined);
+            }
             let val = self.env.get_str(&name);
             map.insert(name, String::from(val));
         }
@@ -255,6 +321,9 @@ impl Shell {
 
 fn run_interactive(shell: &mut Shell) {
     while shell.running {
+        // Pick up LANG / LC_* changes made by the last command
+        locale::update(&shell.env);
+
         // Report any background job completions
         shell.jobs.update_status();
         let messages = shell.jobs.report_and_clean();
@@ -271,8 +340,13 @@ fn run_interactive(shell: &mut Shell) {
             prompt::expand_prompt(&ps1, &ctx)
         };
 
-        // Read a line
-        let line = match shell.readline.readline(&ps1_text) {
+        // Read a line, reporting jobs that finish while the prompt is idle
+        let jobs = &mut shell.jobs;
+        let mut report_jobs = || {
+            jobs.update_status();
+            jobs.report_and_clean()
+        };
+        let mut line = match shell.readline.readline_with(&ps1_text, &mut report_jobs) {
             Some(line) => line,
             None => {
                 // EOF
@@ -281,6 +355,21 @@ fn run_interactive(shell: &mut Shell) {
             }
         };
 
+        // Keep reading until every here-document has its delimiter
+        while lexer::heredoc_incomplete(&line) {
+            let ps2 = match shell.env.get_str("PS2") {
+                "" => prompt::default_ps2(),
+                ps2 => String::from(ps2),
+            };
+            match shell.readline.readline(&ps2) {
+                Some(more) => {
+                    line.push('\n');
+                    line.push_str(&more);
+                }
+                None => break,
+            }
+        }
+
         let trimmed = line.trim();
         if trimmed.is_empty() {
             continue;
@@ -306,12 +395,12 @@ fn run_interactive(shell: &mut Shell) {
     }
 }
 
-#[allow(dead_code)] // Used when argv parsing is implemented
 fn run_script(shell: &mut Shell, path: &str, args: &[String]) {
     shell.interactive = false;
     shell.config.interactive = false;
     shell.env.arg0 = String::from(path);
     shell.env.positional = args.to_vec();
+    locale::update(&shell.env);
 
     match exec::script::run_script_file(shell, path) {
         Ok(status) => {
@@ -327,10 +416,10 @@ fn run_script(shell: &mut Shell, path: &str, args: &[String]) {
     }
 }
 
-#[allow(dead_code)] // Used when argv parsing is implemented (-c flag)
 fn run_command_string(shell: &mut Shell, cmd: &str) {
     shell.interactive = false;
     shell.config.interactive = false;
+    locale::update(&shell.env);
 
     match exec::eval::eval_string(shell, cmd) {
         Ok(status) => {
@@ -350,14 +439,58 @@ fn run_command_string(shell: &mut Shell, cmd: &str) {
 // Entry point
 // ============================================================================
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    let mut shell = Shell::new_interactive();
+/// Called by `_start` (see [`startup`]) with the initial stack pointer.
+///
+/// `vsh` runs interactively, `vsh FILE [ARG...]` runs a script, and
+/// `vsh -c COMMAND [NAME [ARG...]]` runs a command string with `$0` set to
+/// NAME.
+///
+/// # Safety
+/// `sp` must be the stack pointer the kernel started the process with.
+unsafe extern "C" fn vsh_main(sp: *const usize) -> ! {
+    if syscall::sys_abi_handshake() == Err(Errno::ENOTSUP) {
+        eprintln!(
+            "vsh: {}",
+            locale::tr("kernel does not support this syscall ABI")
+        );
+        syscall::sys_exit(126);
+    }
+
+    // SAFETY: the caller passes the initial stack pointer.
+    if let Some(value) = unsafe { startup::env_var(sp, b"MALLOC_PROFILE") } {
+        // Before the first allocation, which would otherwise be freed
+        // without having been counted.
+        ALLOCATOR.start_profile(value);
+    }
 
-    // Parse arguments from argv (passed on the stack by the kernel).
-    // For now, start in interactive mode.
-    // TODO: read argc/argv from the stack or via a syscall.
+    // SAFETY: the caller passes the initial stack pointer.
+    let startup = unsafe { startup::Startup::from_stack(sp) };
+    let mut shell = Shell::new_interactive(&startup.env);
+    locale::update(&shell.env);
 
-    run_interactive(&mut shell);
+    let mut args = startup.args.into_iter();
+    if let Some(arg0) = args.next() {
+        shell.env.arg0 = arg0;
+    }
+    let args: Vec<String> = args.collect();
+
+    match args.first().map(String::as_str) {
+        None => run_interactive(&mut shell),
+        Some("-c") => match args.get(1) {
+            Some(cmd) => {
+                if let Some(name) = args.get(2) {
+                    shell.env.arg0 = name.clone();
+                }
+                shell.env.positional = args.iter().skip(3).cloned().collect();
+                run_command_string(&mut shell, cmd);
+            }
+            None => {
+                let msg = locale::tr("{}: option requires an argument");
+                eprintln!("vsh: {}", i18n::fill(msg, &[&"-c"]));
+                shell.env.last_status = 2;
+            }
+        },
+        Some(path) => run_script(&mut shell, path, &args[1..]),
+    }
     syscall::sys_exit(shell.env.last_status)
 }
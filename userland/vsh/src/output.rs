@@ -47,7 +47,6 @@ impl Writer {
     }
 
     /// Write a string slice followed by a newline.
-    #[allow(dead_code)] // Public API for output
     pub fn write_line(&self, s: &str) {
         self.write_str(s);
         self.write_bytes(b"\n");
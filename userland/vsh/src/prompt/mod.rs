@@ -219,7 +219,6 @@ pub fn default_ps1() -> String {
 }
 
 /// Build the default PS2 prompt: `> `.
-#[allow(dead_code)] // Used for continuation prompts
 pub fn default_ps2() -> String {
     String::from("> ")
 }
@@ -7,6 +7,10 @@
 //! - Tab completion (files, commands, variables)
 //! - Kill ring (Ctrl-K, Ctrl-U, Ctrl-Y)
 //! - Word movement (Alt-B, Alt-F)
+//!
+//! While waiting for a key the editor sleeps in poll(); each time it wakes
+//! without input it reports finished jobs and redraws the line if the
+//! terminal was resized.
 
 extern crate alloc;
 
@@ -90,6 +94,8 @@ pub struct Readline {
     kill_ring: Vec<String>,
     /// Current kill ring index for yank-pop.
     kill_index: usize,
+    /// Terminal size as `(rows, columns)` when last checked.
+    window: Option<(u16, u16)>,
 }
 
 impl Readline {
@@ -99,6 +105,7 @@ impl Readline {
             history: History::new(),
             kill_ring: Vec::new(),
             kill_index: 0,
+            window: None,
         }
     }
 
@@ -107,8 +114,20 @@ impl Readline {
     /// Displays `prompt`, reads input with line editing, and returns the
     /// completed line. Returns `None` on EOF (Ctrl-D on empty line).
     pub fn readline(&mut self, prompt: &str) -> Option<String> {
+        self.readline_with(prompt, &mut Vec::new)
+    }
+
+    /// Like [`readline`](Self::readline), calling `on_idle` whenever the
+    /// wait for input is interrupted. The messages it returns (job status
+    /// changes) are printed above the line being edited.
+    pub fn readline_with(
+        &mut self,
+        prompt: &str,
+        on_idle: &mut dyn FnMut() -> Vec<String>,
+    ) -> Option<String> {
         let out = Writer::stdout();
         out.write_str(prompt);
+        self.window = input::window_size();
 
         let mut buf = Vec::with_capacity(128);
         let mut cursor: usize = 0;
@@ -118,6 +137,9 @@ impl Readline {
         let mut search_query = String::new();
 
         loop {
+            while !input::wait_for_input(input::IDLE_POLL_MS) {
+                self.idle(&out, prompt, &buf, cursor, on_idle);
+            }
             let byte = match input::read_byte() {
                 Some(b) => b,
                 None => {
@@ -537,6 +559,32 @@ impl Readline {
         Some(line)
     }
 
+    /// Handle a wake-up without input: print what `on_idle` reports and
+    /// redraw the line after that or after a terminal resize.
+    fn idle(
+        &mut self,
+        out: &Writer,
+        prompt: &str,
+        buf: &[u8],
+        cursor: usize,
+        on_idle: &mut dyn FnMut() -> Vec<String>,
+    ) {
+        let messages = on_idle();
+        let window = input::window_size();
+        let resized = window != self.window;
+        self.window = window;
+
+        if !messages.is_empty() {
+            out.write_str("\r\x1B[K");
+            for msg in &messages {
+                out.write_line(msg);
+            }
+        }
+        if resized || !messages.is_empty() {
+            self.full_redraw(out, prompt, buf, cursor);
+        }
+    }
+
     /// Redraw the line from the cursor position to the end.
     fn redraw_from_cursor(&self, out: &Writer, buf: &[u8], cursor: usize) {
         // Save cursor, write from cursor to end, clear rest, restore cursor
@@ -0,0 +1,127 @@
+//! Process entry point.
+//!
+//! The kernel starts the shell with argc, the argv pointers and a NULL, and
+//! the envp pointers and a NULL on the stack. `_start` passes that stack
+//! pointer to `vsh_main`, which reads both lists with [`Startup::from_stack`].
+
+use alloc::{string::String, vec::Vec};
+
+#[cfg(target_arch = "x86_64")]
+core::arch::global_asm!(
+    ".globl _start",
+    "_start:",
+    "xor ebp, ebp",
+    "mov rdi, rsp",
+    "and rsp, -16",
+    "call {main}",
+    "ud2",
+    main = sym crate::vsh_main,
+);
+
+#[cfg(target_arch = "aarch64")]
+core::arch::global_asm!(
+    ".globl _start",
+    "_start:",
+    "mov x29, xzr",
+    "mov x0, sp",
+    "bl {main}",
+    "brk #0",
+    main = sym crate::vsh_main,
+);
+
+#[cfg(target_arch = "riscv64")]
+core::arch::global_asm!(
+    ".globl _start",
+    "_start:",
+    "mv fp, zero",
+    "mv a0, sp",
+    "andi sp, sp, -16",
+    "call {main}",
+    "unimp",
+    main = sym crate::vsh_main,
+);
+
+/// The shell's arguments and the environment it was started with.
+pub struct Startup {
+    /// argv, starting with the name the shell was run as.
+    pub args: Vec<String>,
+    /// envp as `NAME=VALUE` strings.
+    pub env: Vec<String>,
+}
+
+impl Startup {
+    /// Copy argv and envp from the initial stack.
+    ///
+    /// # Safety
+    /// `sp` must be the stack pointer the kernel started the process with.
+    pub unsafe fn from_stack(sp: *const usize) -> Self {
+        // SAFETY: the caller guarantees sp points at argc followed by the
+        // NULL-terminated argv and envp arrays.
+        unsafe {
+            let argc = *sp;
+            let argv = sp.add(1) as *const *const u8;
+            let envp = argv.add(argc + 1);
+            Startup {
+                args: read_strings(argv),
+                env: read_strings(envp),
+            }
+        }
+    }
+}
+
+/// The value of environment variable `name`, read from the initial stack
+/// without allocating.
+///
+/// # Safety
+/// `sp` must be the stack pointer the kernel started the process with.
+pub unsafe fn env_var(sp: *const usize, name: &[u8]) -> Option<&'static [u8]> {
+    // SAFETY: as in `Startup::from_stack`; the strings stay on the initial
+    // stack for the life of the process.
+    unsafe {
+        let argc = *sp;
+        let mut list = (sp.add(1) as *const *const u8).add(argc + 1);
+        while !(*list).is_null() {
+            let entry = c_str(*list);
+            if let Some(value) = entry
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix(b"="))
+            {
+                return Some(value);
+            }
+            list = list.add(1);
+        }
+    }
+    None
+}
+
+/// The bytes of a NUL-terminated string.
+///
+/// # Safety
+/// `s` must point to a NUL-terminated string that lives for `'a`.
+unsafe fn c_str<'a>(s: *const u8) -> &'a [u8] {
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        let mut len = 0;
+        while *s.add(len) != 0 {
+            len += 1;
+        }
+        core::slice::from_raw_parts(s, len)
+    }
+}
+
+/// Copy a NULL-terminated array of C strings. Invalid UTF-8 is replaced.
+///
+/// # Safety
+/// `list` must point to a NULL-terminated array of NUL-terminated strings.
+unsafe fn read_strings(mut list: *const *const u8) -> Vec<String> {
+    let mut strings = Vec::new();
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        while !(*list).is_null() {
+            let bytes = c_str(*list);
+            strings.push(String::from_utf8_lossy(bytes).into_owned());
+            list = list.add(1);
+        }
+    }
+    strings
+}
@@ -1,12 +1,15 @@
 //! Raw syscall interface for VeridianOS.
 //!
-//! Provides inline assembly wrappers for the `syscall` instruction and
-//! all syscall number constants needed by vsh.  This is a self-contained
-//! copy of the relevant parts of `userland/rust-std` so that vsh can be
-//! built as a standalone `no_std` binary without workspace dependencies.
+//! Provides inline assembly wrappers for the `syscall` instruction.  This
+//! is a self-contained copy of the relevant parts of `userland/rust-std` so
+//! that vsh can be built as a standalone `no_std` binary; the syscall
+//! numbers come from the shared `veridian-abi` crate.
+
+use errno::Errno;
+use veridian_abi::{AbiVersion, ABI_VERSION};
 
 // ---------------------------------------------------------------------------
-// Raw syscall wrappers (x86_64 only for now; aarch64/riscv64 stubs below)
+// Raw syscall wrappers (syscall on x86_64, svc #0 on AArch64, ecall on RISC-V)
 // ---------------------------------------------------------------------------
 
 /// Invoke a syscall with 0 arguments.
@@ -303,60 +306,10 @@ pub unsafe fn syscall6(
 }
 
 // ---------------------------------------------------------------------------
-// Syscall number constants (must match kernel/src/syscall/mod.rs)
+// Syscall number constants
 // ---------------------------------------------------------------------------
 
-// Process management
-pub const SYS_PROCESS_EXIT: usize = 11;
-pub const SYS_PROCESS_FORK: usize = 12;
-pub const SYS_PROCESS_EXEC: usize = 13;
-pub const SYS_PROCESS_WAIT: usize = 14;
-pub const SYS_PROCESS_GETPID: usize = 15;
-#[allow(dead_code)]
-pub const SYS_PROCESS_GETPPID: usize = 16;
-
-// Memory management
-pub const SYS_MEMORY_MAP: usize = 20;
-pub const SYS_MEMORY_UNMAP: usize = 21;
-
-// File operations
-pub const SYS_FILE_OPEN: usize = 50;
-pub const SYS_FILE_CLOSE: usize = 51;
-pub const SYS_FILE_READ: usize = 52;
-pub const SYS_FILE_WRITE: usize = 53;
-#[allow(dead_code)]
-pub const SYS_FILE_STAT: usize = 55;
-#[allow(dead_code)]
-pub const SYS_FILE_DUP: usize = 57;
-pub const SYS_FILE_DUP2: usize = 58;
-pub const SYS_FILE_PIPE: usize = 59;
-
-// Directory operations
-#[allow(dead_code)]
-pub const SYS_DIR_OPENDIR: usize = 62;
-#[allow(dead_code)]
-pub const SYS_DIR_READDIR: usize = 63;
-#[allow(dead_code)]
-pub const SYS_DIR_CLOSEDIR: usize = 64;
-
-// Extended process operations
-pub const SYS_PROCESS_GETCWD: usize = 110;
-pub const SYS_PROCESS_CHDIR: usize = 111;
-#[allow(dead_code)]
-pub const SYS_PROCESS_KILL: usize = 113;
-
-// Signal handling
-#[allow(dead_code)]
-pub const SYS_SIGACTION: usize = 120;
-
-// Extended filesystem
-#[allow(dead_code)]
-pub const SYS_FILE_STAT_PATH: usize = 150;
-pub const SYS_FILE_ACCESS: usize = 153;
-
-// Identity
-#[allow(dead_code)]
-pub const SYS_GETUID: usize = 170;
+pub use veridian_abi::nr::*;
 
 // mmap constants
 pub const PROT_READ: usize = 0x1;
@@ -389,6 +342,33 @@ pub const R_OK: usize = 4;
 #[allow(dead_code)]
 pub const WNOHANG: i32 = 1;
 
+// poll events
+pub const POLLIN: i16 = 0x001;
+
+// Terminal ioctl requests
+pub const TIOCGWINSZ: usize = 0x5413;
+
+// Error numbers (negated in syscall returns)
+pub const EINTR: isize = 7;
+
+/// `struct pollfd`.
+#[repr(C)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+/// `struct winsize`.
+#[repr(C)]
+#[derive(Default)]
+pub struct Winsize {
+    pub rows: u16,
+    pub cols: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
 // ---------------------------------------------------------------------------
 // Higher-level wrappers
 // ---------------------------------------------------------------------------
@@ -432,6 +412,16 @@ pub fn sys_exit(status: i32) -> ! {
     loop {}
 }
 
+/// Check that the kernel can run binaries built against this ABI version.
+///
+/// Kernels that predate the handshake fail it with `ENOSYS`; their
+/// numbering is ABI 1.0.
+pub fn sys_abi_handshake() -> Result<AbiVersion, Errno> {
+    // SAFETY: the handshake only reads its argument.
+    let ret = unsafe { syscall1(SYS_ABI_VERSION, ABI_VERSION.to_raw()) };
+    Errno::from_ret(ret).map(AbiVersion::from_raw)
+}
+
 /// Get the current process ID.
 pub fn sys_getpid() -> i32 {
     // SAFETY: getpid has no side effects.
@@ -479,6 +469,21 @@ pub fn sys_open(path: *const u8, flags: usize, mode: usize) -> isize {
     unsafe { syscall3(SYS_FILE_OPEN, path as usize, flags, mode) }
 }
 
+/// Read `linux_dirent64` records for the directory open on `fd` into
+/// `buf`. Returns bytes written, 0 at the end of the directory, or
+/// negative error.
+pub fn sys_getdents64(fd: i32, buf: &mut [u8]) -> isize {
+    // SAFETY: Kernel writes at most buf.len() bytes.
+    unsafe {
+        syscall3(
+            SYS_GETDENTS64,
+            fd as usize,
+            buf.as_mut_ptr() as usize,
+            buf.len(),
+        )
+    }
+}
+
 /// Close a file descriptor.
 pub fn sys_close(fd: i32) -> isize {
     // SAFETY: Kernel validates the fd.
@@ -526,6 +531,19 @@ pub fn sys_mmap(addr: usize, length: usize, prot: usize, flags: usize) -> isize
     }
 }
 
+/// Register heap statistics for `/proc/<pid>/heap`.
+pub fn sys_heap_profile(stats: &'static veridian_abi::heap_profile::HeapStats) -> isize {
+    // SAFETY: The statistics are static, so the kernel may read them for as
+    // long as the process runs.
+    unsafe {
+        syscall2(
+            SYS_HEAP_PROFILE,
+            stats as *const _ as usize,
+            core::mem::size_of_val(stats),
+        )
+    }
+}
+
 /// Unmap memory pages.
 #[allow(dead_code)]
 pub fn sys_munmap(addr: usize, length: usize) -> isize {
@@ -538,3 +556,30 @@ pub fn sys_access(path: *const u8, mode: usize) -> isize {
     // SAFETY: Kernel validates the path pointer.
     unsafe { syscall2(SYS_FILE_ACCESS, path as usize, mode) }
 }
+
+/// Wait for events on `fds`. Returns the number of ready entries, 0 on
+/// timeout, or negative error (`-EINTR` when a signal arrived).
+pub fn sys_poll(fds: &mut [PollFd], timeout_ms: i32) -> isize {
+    // SAFETY: Kernel reads and updates exactly fds.len() entries.
+    unsafe {
+        syscall3(
+            SYS_FILE_POLL,
+            fds.as_mut_ptr() as usize,
+            fds.len(),
+            timeout_ms as usize,
+        )
+    }
+}
+
+/// Get the window size of the terminal on `fd`.
+pub fn sys_get_winsize(fd: i32, ws: &mut Winsize) -> isize {
+    // SAFETY: Kernel writes exactly one struct winsize.
+    unsafe {
+        syscall3(
+            SYS_FILE_IOCTL,
+            fd as usize,
+            TIOCGWINSZ,
+            ws as *mut Winsize as usize,
+        )
+    }
+}
@@ -9,6 +9,8 @@ extern crate alloc;
 
 use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
 
+use crate::error::{Result, VshError};
+
 /// Attributes that can be set on a variable.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct VarAttrs {
@@ -204,11 +206,11 @@ impl ShellEnv {
     }
 
     /// Set a variable in the current (innermost) scope.
-    pub fn set(&mut self, name: &str, value: &str) -> Result<(), &'static str> {
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
         // Check readonly in all scopes
         if let Some(var) = self.get(name) {
             if var.attrs.readonly {
-                return Err("readonly variable");
+                return Err(VshError::ReadOnly(String::from(name)));
             }
         }
 
@@ -232,11 +234,11 @@ impl ShellEnv {
     }
 
     /// Set a variable in the global scope.
-    pub fn set_global(&mut self, name: &str, value: &str) -> Result<(), &'static str> {
+    pub fn set_global(&mut self, name: &str, value: &str) -> Result<()> {
         // Check readonly
         if let Some(var) = self.get(name) {
             if var.attrs.readonly {
-                return Err("readonly variable");
+                return Err(VshError::ReadOnly(String::from(name)));
             }
         }
 
@@ -258,7 +260,7 @@ impl ShellEnv {
     }
 
     /// Set a local variable in the innermost scope only (for `local` builtin).
-    pub fn set_local(&mut self, name: &str, value: &str) -> Result<(), &'static str> {
+    pub fn set_local(&mut self, name: &str, value: &str) -> Result<()> {
         let scope = self.scopes.last_mut().unwrap();
         scope.vars.insert(
             String::from(name),
@@ -271,11 +273,11 @@ impl ShellEnv {
     }
 
     /// Unset a variable.
-    pub fn unset(&mut self, name: &str) -> Result<(), &'static str> {
+    pub fn unset(&mut self, name: &str) -> Result<()> {
         // Check readonly
         if let Some(var) = self.get(name) {
             if var.attrs.readonly {
-                return Err("readonly variable");
+                return Err(VshError::ReadOnly(String::from(name)));
             }
         }
         for scope in self.scopes.iter_mut().rev() {
@@ -325,15 +327,10 @@ impl ShellEnv {
     // --- Array operations ---
 
     /// Set an indexed array element.
-    pub fn set_array_element(
-        &mut self,
-        name: &str,
-        index: usize,
-        value: &str,
-    ) -> Result<(), &'static str> {
+    pub fn set_array_element(&mut self, name: &str, index: usize, value: &str) -> Result<()> {
         if let Some(var) = self.get(name) {
             if var.attrs.readonly {
-                return Err("readonly variable");
+                return Err(VshError::ReadOnly(String::from(name)));
             }
         }
 
@@ -374,6 +371,21 @@ impl ShellEnv {
         Ok(())
     }
 
+    /// Replace `name` in the global scope with an indexed array of `values`
+    /// (for shell-maintained arrays such as `PIPESTATUS`).
+    pub fn set_global_array(&mut self, name: &str, values: Vec<String>) {
+        self.scopes[0].vars.insert(
+            String::from(name),
+            Variable {
+                value: VarValue::Array(values),
+                attrs: VarAttrs {
+                    is_array: true,
+                    ..VarAttrs::default()
+                },
+            },
+        );
+    }
+
     /// Get an indexed array element.
     pub fn get_array_element(&self, name: &str, index: usize) -> Option<&str> {
         match self.get(name) {
@@ -422,15 +434,10 @@ impl ShellEnv {
     }
 
     /// Set an associative array element.
-    pub fn set_assoc_element(
-        &mut self,
-        name: &str,
-        key: &str,
-        value: &str,
-    ) -> Result<(), &'static str> {
+    pub fn set_assoc_element(&mut self, name: &str, key: &str, value: &str) -> Result<()> {
         if let Some(var) = self.get(name) {
             if var.attrs.readonly {
-                return Err("readonly variable");
+                return Err(VshError::ReadOnly(String::from(name)));
             }
         }
 